@@ -18,6 +18,7 @@ const Appearance = lazy(() => import('./pages/Appearance'));
 const Sidebars = lazy(() => import('./pages/Sidebars'));
 const SEO = lazy(() => import('./pages/SEO'));
 const Cache = lazy(() => import('./pages/Cache'));
+const DeviceLogin = lazy(() => import('./pages/DeviceLogin'));
 const ThemePreview = lazy(() => import('./pages/ThemePreview'));
 const Themes = lazy(() => import('./pages/Themes'));
 const GoogleAnalyticsDashboard = lazy(() => import('./pages/analytics/GoogleAnalyticsDashboard'));
@@ -853,6 +854,11 @@ function App() {
         } />
 
         {/* System */}
+        <Route path="device" element={
+          <Suspense fallback={<PageLoader />}>
+            <DeviceLogin />
+          </Suspense>
+        } />
         <Route path="settings" element={<SettingsListPage />} />
         <Route path="settings/site-mode" element={
           <Suspense fallback={<PageLoader />}>
@@ -0,0 +1,142 @@
+import { useState, useEffect, useCallback } from 'react'
+import { useSearchParams } from 'react-router-dom'
+import { Terminal, CheckCircle, XCircle, Loader2 } from 'lucide-react'
+import toast from 'react-hot-toast'
+import api from '../api/client'
+
+interface DeviceRequest {
+  user_code: string
+  client_id: string
+  scopes: string[]
+  expires_at: string
+}
+
+type Outcome = 'approved' | 'denied' | null
+
+/**
+ * Approve or deny a device login (OAuth2 device authorization grant).
+ * The CLI prints a user code and links here with `?user_code=`.
+ */
+export default function DeviceLogin() {
+  const [searchParams] = useSearchParams()
+  const [userCode, setUserCode] = useState(searchParams.get('user_code') ?? '')
+  const [request, setRequest] = useState<DeviceRequest | null>(null)
+  const [isLoading, setIsLoading] = useState(false)
+  const [isResolving, setIsResolving] = useState(false)
+  const [outcome, setOutcome] = useState<Outcome>(null)
+
+  const lookup = useCallback(async (code: string) => {
+    if (!code.trim()) return
+    setIsLoading(true)
+    setRequest(null)
+    try {
+      const res = await api.get('/v1/auth/device', { params: { user_code: code } })
+      setRequest(res.data)
+    } catch (error) {
+      console.error('Failed to look up device code:', error)
+      toast.error('Unknown or expired code')
+    } finally {
+      setIsLoading(false)
+    }
+  }, [])
+
+  useEffect(() => {
+    const code = searchParams.get('user_code')
+    if (code) lookup(code)
+  }, [searchParams, lookup])
+
+  const resolve = async (approve: boolean) => {
+    if (!request) return
+    setIsResolving(true)
+    try {
+      await api.post('/v1/auth/device', { user_code: request.user_code, approve })
+      setOutcome(approve ? 'approved' : 'denied')
+    } catch (error) {
+      console.error('Failed to resolve device login:', error)
+      toast.error('The code has expired, start the login again')
+    } finally {
+      setIsResolving(false)
+    }
+  }
+
+  return (
+    <div className="max-w-md mx-auto py-12">
+      <div className="bg-white dark:bg-gray-800 rounded-xl border border-gray-200 dark:border-gray-700 p-6 space-y-6">
+        <div className="flex items-center gap-3">
+          <Terminal className="w-6 h-6 text-primary-600" />
+          <h1 className="text-xl font-semibold text-gray-900 dark:text-white">Device login</h1>
+        </div>
+
+        {outcome === 'approved' && (
+          <div className="flex items-center gap-2 text-green-600">
+            <CheckCircle className="w-5 h-5" />
+            <span>Approved. You can return to your device.</span>
+          </div>
+        )}
+        {outcome === 'denied' && (
+          <div className="flex items-center gap-2 text-red-600">
+            <XCircle className="w-5 h-5" />
+            <span>The login request was denied.</span>
+          </div>
+        )}
+
+        {outcome === null && (
+          <>
+            <form
+              className="space-y-2"
+              onSubmit={(e) => {
+                e.preventDefault()
+                lookup(userCode)
+              }}
+            >
+              <label className="block text-sm text-gray-600 dark:text-gray-300" htmlFor="user-code">
+                Enter the code shown on your device
+              </label>
+              <div className="flex gap-2">
+                <input
+                  id="user-code"
+                  value={userCode}
+                  onChange={(e) => setUserCode(e.target.value.toUpperCase())}
+                  placeholder="ABCD-EFGH"
+                  className="flex-1 px-3 py-2 rounded-lg border border-gray-300 dark:border-gray-600 bg-transparent font-mono tracking-widest"
+                />
+                <button
+                  type="submit"
+                  disabled={isLoading}
+                  className="px-4 py-2 rounded-lg bg-gray-100 dark:bg-gray-700 text-sm"
+                >
+                  {isLoading ? <Loader2 className="w-4 h-4 animate-spin" /> : 'Continue'}
+                </button>
+              </div>
+            </form>
+
+            {request && (
+              <div className="space-y-4">
+                <p className="text-sm text-gray-600 dark:text-gray-300">
+                  <span className="font-medium">{request.client_id}</span> is asking to sign in as you
+                  {request.scopes.length > 0 && <> with access to {request.scopes.join(', ')}</>}.
+                </p>
+                <div className="flex gap-2">
+                  <button
+                    onClick={() => resolve(true)}
+                    disabled={isResolving}
+                    className="flex-1 px-4 py-2 rounded-lg bg-primary-600 text-white text-sm"
+                  >
+                    Approve
+                  </button>
+                  <button
+                    onClick={() => resolve(false)}
+                    disabled={isResolving}
+                    className="flex-1 px-4 py-2 rounded-lg border border-gray-300 dark:border-gray-600 text-sm"
+                  >
+                    Deny
+                  </button>
+                </div>
+              </div>
+            )}
+          </>
+        )}
+      </div>
+    </div>
+  )
+}
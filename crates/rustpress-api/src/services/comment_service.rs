@@ -2,9 +2,10 @@
 
 use chrono::{DateTime, Utc};
 use rustpress_core::error::{Error, Result};
+use rustpress_core::tenant::current_site;
 use rustpress_database::repository::comments::{
     CommentListParams, CommentRow, CommentStatus, CommentWithAuthor, CommentsRepository,
-    CreateComment, UpdateComment,
+    CreateComment, PostCommentSettingsRow, SavePostCommentSettings, UpdateComment,
 };
 use rustpress_database::repository::options::OptionsRepository;
 use serde::{Deserialize, Serialize};
@@ -85,6 +86,87 @@ pub struct BatchModerateRequest {
     pub status: CommentStatus,
 }
 
+/// Comment waiting in the moderation queue, with what the spam check saw
+#[derive(Debug, Clone, Serialize)]
+pub struct QueuedCommentResponse {
+    #[serde(flatten)]
+    pub comment: CommentResponse,
+    pub spam_score: Option<f64>,
+    pub spam_reasons: Option<serde_json::Value>,
+    pub author_ip: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+/// Page of the moderation queue
+#[derive(Debug, Clone, Serialize)]
+pub struct ModerationQueueResponse {
+    pub comments: Vec<QueuedCommentResponse>,
+    pub total: u64,
+    pub page: u64,
+    pub per_page: u64,
+    pub total_pages: u64,
+    pub counts: HashMap<String, i64>,
+}
+
+/// Comment settings of a post
+///
+/// `None` overrides follow the site-wide discussion settings.
+#[derive(Debug, Clone, Serialize)]
+pub struct PostCommentSettings {
+    pub post_id: Uuid,
+    pub comments_open: bool,
+    pub require_moderation: Option<bool>,
+    pub max_depth: Option<i32>,
+    pub closes_at: Option<DateTime<Utc>>,
+}
+
+/// Update post comment settings request
+#[derive(Debug, Clone, Deserialize)]
+pub struct UpdatePostCommentSettingsRequest {
+    #[serde(default = "default_comments_open")]
+    pub comments_open: bool,
+    #[serde(default)]
+    pub require_moderation: Option<bool>,
+    #[serde(default)]
+    pub max_depth: Option<i32>,
+    #[serde(default)]
+    pub closes_at: Option<DateTime<Utc>>,
+}
+
+fn default_comments_open() -> bool {
+    true
+}
+
+/// Reply depth when the site does not configure one
+const DEFAULT_THREAD_DEPTH: i32 = 5;
+
+/// Deepest threading a site or post may configure
+const MAX_THREAD_DEPTH: i32 = 10;
+
+impl From<CommentRow> for QueuedCommentResponse {
+    fn from(mut row: CommentRow) -> Self {
+        Self {
+            spam_score: row.spam_score,
+            spam_reasons: row.spam_reasons.take(),
+            author_ip: row.author_ip.take(),
+            user_agent: row.user_agent.take(),
+            comment: CommentResponse::from(row),
+        }
+    }
+}
+
+impl From<PostCommentSettingsRow> for PostCommentSettings {
+    fn from(row: PostCommentSettingsRow) -> Self {
+        Self {
+            post_id: row.post_id,
+            comments_open: comments_open(&row),
+            require_moderation: row.require_moderation,
+            max_depth: row.max_depth,
+            closes_at: row.closes_at,
+        }
+    }
+}
+
 impl From<CommentRow> for CommentResponse {
     fn from(row: CommentRow) -> Self {
         let author = CommentAuthorResponse {
@@ -154,11 +236,11 @@ pub struct CommentService {
 }
 
 impl CommentService {
-    /// Create a new comment service
+    /// Create a new comment service for the site the current request serves
     pub fn new(pool: PgPool) -> Self {
         Self {
             pool,
-            site_id: None,
+            site_id: current_site(),
         }
     }
 
@@ -203,10 +285,35 @@ impl CommentService {
         }
     }
 
+    /// Get an integer option value, accepting numbers stored as strings
+    async fn get_int_option(&self, name: &str) -> Option<i64> {
+        match self.options_repo().get(name).await {
+            Ok(Some(value)) => value
+                .as_i64()
+                .or_else(|| value.as_str().and_then(|s| s.trim().parse().ok())),
+            _ => None,
+        }
+    }
+
+    /// Site-wide reply depth; a single level when threading is off
+    async fn max_thread_depth(&self) -> i32 {
+        if !self.get_bool_option("comments_nested", true).await {
+            return 1;
+        }
+        self.get_int_option("comments_nested_depth")
+            .await
+            .map(|depth| depth.clamp(1, MAX_THREAD_DEPTH as i64) as i32)
+            .unwrap_or(DEFAULT_THREAD_DEPTH)
+    }
+
     /// Determine the initial comment status based on settings and author history
+    ///
+    /// `require_moderation` is the post's override of the site-wide
+    /// `comment_moderation` option.
     async fn determine_initial_status(
         &self,
         spam_score: f64,
+        require_moderation: Option<bool>,
         user_id: Option<Uuid>,
         author_email: &Option<String>,
     ) -> CommentStatus {
@@ -221,7 +328,11 @@ impl CommentService {
         }
 
         // Check if manual moderation is required for all comments
-        let require_moderation = self.get_bool_option("comment_moderation", false).await;
+        let require_moderation = match require_moderation {
+            Some(true) => return CommentStatus::Pending,
+            Some(false) => return CommentStatus::Approved,
+            None => self.get_bool_option("comment_moderation", false).await,
+        };
         if require_moderation {
             return CommentStatus::Pending;
         }
@@ -294,12 +405,52 @@ impl CommentService {
             }
         }
 
+        let settings = self
+            .repo()
+            .find_post_settings(request.post_id)
+            .await?
+            .ok_or_else(|| Error::not_found("Post", request.post_id.to_string()))?;
+        let comments_enabled = self.get_bool_option("comments_enabled", true).await;
+        if let Some(reason) = closed_reason(&settings, comments_enabled, Utc::now()) {
+            return Err(Error::validation(reason));
+        }
+
+        if let Some(parent_id) = request.parent_id {
+            let parent = self
+                .repo()
+                .find_by_id(parent_id)
+                .await?
+                .ok_or_else(|| Error::not_found("Comment", parent_id.to_string()))?;
+            if parent.post_id != request.post_id {
+                return Err(Error::invalid_input(
+                    "parent_id",
+                    "Parent comment belongs to another post",
+                ));
+            }
+            if parent.status != CommentStatus::Approved.to_string() {
+                return Err(Error::invalid_input(
+                    "parent_id",
+                    "Only approved comments can be replied to",
+                ));
+            }
+            let max_depth = match settings.max_depth {
+                Some(depth) => depth,
+                None => self.max_thread_depth().await,
+            };
+            check_reply_depth(parent.depth, max_depth)?;
+        }
+
         // Check spam (simple heuristics for now)
         let spam_score = self.calculate_spam_score(&request, &ip, &user_agent);
 
         // Determine initial status based on settings and author history
         let initial_status = self
-            .determine_initial_status(spam_score, user_id, &request.author_email)
+            .determine_initial_status(
+                spam_score,
+                settings.require_moderation,
+                user_id,
+                &request.author_email,
+            )
             .await;
 
         // Render content to HTML (basic for now)
@@ -344,6 +495,7 @@ impl CommentService {
         post_id: Option<Uuid>,
         status: Option<CommentStatus>,
         search: Option<String>,
+        filter: Option<String>,
     ) -> Result<CommentsListResponse> {
         let params = CommentListParams {
             page: page.max(1),
@@ -351,6 +503,7 @@ impl CommentService {
             post_id,
             status,
             search,
+            filter,
             order_desc: true,
             ..Default::default()
         };
@@ -463,6 +616,92 @@ impl CommentService {
         self.repo().count_by_status().await
     }
 
+    /// Comments waiting for a moderator, oldest first
+    ///
+    /// Lists pending comments, or those held as spam.
+    pub async fn moderation_queue(
+        &self,
+        status: CommentStatus,
+        page: u64,
+        per_page: u64,
+    ) -> Result<ModerationQueueResponse> {
+        if !matches!(status, CommentStatus::Pending | CommentStatus::Spam) {
+            return Err(Error::invalid_input(
+                "status",
+                "The moderation queue holds pending or spam comments",
+            ));
+        }
+
+        let params = CommentListParams {
+            page: page.max(1),
+            per_page: per_page.clamp(1, 100),
+            status: Some(status),
+            order_by: Some("created_at".to_string()),
+            order_desc: false,
+            ..Default::default()
+        };
+
+        let (comments, total) = self.repo().list(&params).await?;
+        let counts = self.repo().count_by_status().await?;
+
+        let total_pages = (total as f64 / params.per_page as f64).ceil() as u64;
+
+        Ok(ModerationQueueResponse {
+            comments: comments
+                .into_iter()
+                .map(QueuedCommentResponse::from)
+                .collect(),
+            total,
+            page: params.page,
+            per_page: params.per_page,
+            total_pages,
+            counts,
+        })
+    }
+
+    /// Get the comment settings of a post
+    pub async fn get_post_settings(&self, post_id: Uuid) -> Result<PostCommentSettings> {
+        self.repo()
+            .find_post_settings(post_id)
+            .await?
+            .map(PostCommentSettings::from)
+            .ok_or_else(|| Error::not_found("Post", post_id.to_string()))
+    }
+
+    /// Replace the comment settings of a post
+    pub async fn update_post_settings(
+        &self,
+        post_id: Uuid,
+        request: UpdatePostCommentSettingsRequest,
+    ) -> Result<PostCommentSettings> {
+        if let Some(depth) = request.max_depth {
+            if !(1..=MAX_THREAD_DEPTH).contains(&depth) {
+                return Err(Error::invalid_input(
+                    "max_depth",
+                    format!("Must be between 1 and {}", MAX_THREAD_DEPTH),
+                ));
+            }
+        }
+
+        let settings = SavePostCommentSettings {
+            comment_status: if request.comments_open {
+                "open"
+            } else {
+                "closed"
+            }
+            .to_string(),
+            require_moderation: request.require_moderation,
+            max_depth: request.max_depth,
+            closes_at: request.closes_at,
+        };
+
+        self.repo()
+            .save_post_settings(post_id, &settings)
+            .await?
+            .map(PostCommentSettings::from)
+            .ok_or_else(|| Error::not_found("Post", post_id.to_string()))
+    }
+
     // =====================
     // Helper methods
     // =====================
@@ -523,6 +762,40 @@ impl CommentService {
     }
 }
 
+/// Whether a post's own setting lets comments in
+fn comments_open(settings: &PostCommentSettingsRow) -> bool {
+    settings.comment_status.as_deref().unwrap_or("open") == "open"
+}
+
+/// Why a post does not take new comments, if it does not
+fn closed_reason(
+    settings: &PostCommentSettingsRow,
+    comments_enabled: bool,
+    now: DateTime<Utc>,
+) -> Option<&'static str> {
+    if !comments_enabled {
+        Some("Comments are disabled on this site")
+    } else if settings.post_status != "published" {
+        Some("Comments are only accepted on published posts")
+    } else if !comments_open(settings) || settings.closes_at.is_some_and(|at| at <= now) {
+        Some("Comments are closed for this post")
+    } else {
+        None
+    }
+}
+
+/// Reject a reply that would nest deeper than `max_depth` levels
+fn check_reply_depth(parent_depth: i32, max_depth: i32) -> Result<()> {
+    // Top-level comments are depth 0
+    if parent_depth + 1 >= max_depth {
+        return Err(Error::invalid_input(
+            "parent_id",
+            format!("Replies are limited to {} levels", max_depth),
+        ));
+    }
+    Ok(())
+}
+
 /// Standalone spam score calculation (for testing without database)
 fn calculate_spam_score_impl(
     request: &CreateCommentRequest,
@@ -650,4 +923,45 @@ mod tests {
         let score = calculate_spam_score_impl(&request, &None, &None);
         assert!(score > 0.5);
     }
+
+    fn post_settings() -> PostCommentSettingsRow {
+        PostCommentSettingsRow {
+            post_id: Uuid::new_v4(),
+            post_status: "published".to_string(),
+            comment_status: Some("open".to_string()),
+            require_moderation: None,
+            max_depth: None,
+            closes_at: None,
+        }
+    }
+
+    #[test]
+    fn test_closed_reason() {
+        let now = Utc::now();
+        let mut settings = post_settings();
+        assert_eq!(closed_reason(&settings, true, now), None);
+        assert!(closed_reason(&settings, false, now).is_some());
+
+        settings.closes_at = Some(now + chrono::Duration::days(1));
+        assert_eq!(closed_reason(&settings, true, now), None);
+        settings.closes_at = Some(now);
+        assert!(closed_reason(&settings, true, now).is_some());
+
+        settings.closes_at = None;
+        settings.comment_status = Some("closed".to_string());
+        assert!(closed_reason(&settings, true, now).is_some());
+
+        settings.comment_status = None;
+        settings.post_status = "draft".to_string();
+        assert!(closed_reason(&settings, true, now).is_some());
+    }
+
+    #[test]
+    fn test_reply_depth() {
+        assert!(check_reply_depth(0, 5).is_ok());
+        assert!(check_reply_depth(3, 5).is_ok());
+        assert!(check_reply_depth(4, 5).is_err());
+        // Threading off: top-level comments only
+        assert!(check_reply_depth(0, 1).is_err());
+    }
 }
@@ -10,7 +10,10 @@
 use chrono::{DateTime, Utc};
 use rustpress_core::error::{Error, Result};
 use rustpress_core::service::SortOrder;
+use rustpress_core::tenant::current_site;
+use rustpress_database::filter::MEDIA_FILTERS;
 use rustpress_database::models::MediaRow;
+use rustpress_database::repository::Versioning;
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
 use uuid::Uuid;
@@ -80,8 +83,11 @@ pub struct MediaResponse {
     pub height: Option<i32>,
     pub duration: Option<i32>,
     pub metadata: serde_json::Value,
+    pub folder_id: Option<Uuid>,
+    pub tags: Vec<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub version: i64,
 }
 
 /// Paginated media response
@@ -100,6 +106,9 @@ pub struct UploadMediaMetadata {
     pub alt_text: Option<String>,
     pub title: Option<String>,
     pub description: Option<String>,
+    /// Folder to file the upload in
+    #[serde(default)]
+    pub folder_id: Option<Uuid>,
 }
 
 /// Update media request
@@ -108,6 +117,9 @@ pub struct UpdateMediaRequest {
     pub alt_text: Option<String>,
     pub title: Option<String>,
     pub description: Option<String>,
+    /// Version the client last read; stale updates are rejected
+    #[serde(default)]
+    pub version: Option<i64>,
 }
 
 /// Media list query parameters
@@ -121,6 +133,18 @@ pub struct MediaListParams {
     pub search: Option<String>,
     pub sort_by: Option<String>,
     pub sort_order: Option<String>,
+    /// Filter expression, e.g. `status:published AND category:rust`
+    pub filter: Option<String>,
+    /// Only media in this folder
+    pub folder_id: Option<Uuid>,
+    /// Only media with this tag
+    pub tag: Option<String>,
+    /// Only images without alt text
+    #[serde(default)]
+    pub missing_alt: bool,
+    /// Only media no content references
+    #[serde(default)]
+    pub unused: bool,
 }
 
 impl From<MediaRow> for MediaResponse {
@@ -143,8 +167,11 @@ impl From<MediaRow> for MediaResponse {
             height: row.height,
             duration: row.duration,
             metadata: row.metadata,
+            folder_id: row.folder_id,
+            tags: row.tags,
             created_at: row.created_at,
             updated_at: row.updated_at,
+            version: row.version,
         }
     }
 }
@@ -158,11 +185,11 @@ pub struct MediaService {
 }
 
 impl MediaService {
-    /// Create a new media service
+    /// Create a new media service for the site the current request serves
     pub fn new(pool: PgPool) -> Self {
         Self {
             pool,
-            tenant_id: None,
+            tenant_id: current_site(),
             base_url: String::new(),
         }
     }
@@ -205,6 +232,7 @@ impl MediaService {
             alt_text: None,
             title: None,
             description: None,
+            folder_id: None,
         });
 
         let media = MediaRow {
@@ -224,9 +252,12 @@ impl MediaService {
             height: dimensions.map(|(_, h)| h),
             duration,
             metadata: serde_json::json!({}),
+            folder_id: media_metadata.folder_id,
+            tags: Vec::new(),
             created_at: now,
             updated_at: now,
             deleted_at: None,
+            version: 1,
         };
 
         let created = self.create(&media).await?;
@@ -260,11 +291,32 @@ impl MediaService {
         if let Some(ref search) = params.search {
             let escaped = search.replace('\'', "''");
             conditions.push(format!(
-                "(filename ILIKE '%{}%' OR original_filename ILIKE '%{}%' OR title ILIKE '%{}%' OR alt_text ILIKE '%{}%')",
-                escaped, escaped, escaped, escaped
+                "(filename ILIKE '%{0}%' OR original_filename ILIKE '%{0}%' OR title ILIKE '%{0}%' OR alt_text ILIKE '%{0}%' OR description ILIKE '%{0}%' OR '{0}' = ANY(tags))",
+                escaped
             ));
         }
 
+        if let Some(folder_id) = params.folder_id {
+            conditions.push(format!("folder_id = '{}'", folder_id));
+        }
+
+        if let Some(ref tag) = params.tag {
+            conditions.push(format!("'{}' = ANY(tags)", tag.replace('\'', "''")));
+        }
+
+        if params.missing_alt {
+            conditions.push("mime_type LIKE 'image/%' AND COALESCE(alt_text, '') = ''".to_string());
+        }
+
+        if params.unused {
+            conditions.push(
+                "NOT EXISTS (SELECT 1 FROM media_usage mu WHERE mu.media_id = media.id)"
+                    .to_string(),
+            );
+        }
+
+        conditions.extend(MEDIA_FILTERS.parse_conditions(params.filter.as_deref())?);
+
         let where_clause = conditions.join(" AND ");
         let order_by = params.sort_by.as_deref().unwrap_or("created_at");
         let order_dir = if sort_order == SortOrder::Desc {
@@ -321,26 +373,42 @@ impl MediaService {
             .await?
             .ok_or_else(|| Error::not_found("Media", id.to_string()))?;
 
-        let query = r#"
+        let query = format!(
+            r#"
             UPDATE media SET
                 alt_text = $2,
                 title = $3,
                 description = $4,
-                updated_at = NOW()
-            WHERE id = $1
+                updated_at = NOW(),
+                version = version + 1
+            WHERE id = $1 AND {}
             RETURNING *
-        "#;
+        "#,
+            Versioning::guard(5)
+        );
 
-        let updated: MediaRow = sqlx::query_as(query)
+        let updated: Option<MediaRow> = sqlx::query_as(&query)
             .bind(id)
             .bind(request.alt_text.or(existing.alt_text))
             .bind(request.title.or(existing.title))
             .bind(request.description.or(existing.description))
-            .fetch_one(&self.pool)
+            .bind(request.version.unwrap_or(existing.version))
+            .fetch_optional(&self.pool)
             .await
             .map_err(|e| Error::database_with_source("Failed to update media", e))?;
 
-        Ok(MediaResponse::from(updated))
+        match updated {
+            Some(row) => Ok(MediaResponse::from(row)),
+            None => Err(Versioning::resolve_conflict(
+                &self.pool,
+                "Media",
+                "media",
+                "id",
+                id,
+                "deleted_at IS NULL",
+            )
+            .await),
+        }
     }
 
     /// Delete a media item (soft delete)
@@ -452,8 +520,8 @@ impl MediaService {
     async fn create(&self, media: &MediaRow) -> Result<MediaRow> {
         sqlx::query_as::<_, MediaRow>(
             r#"
-            INSERT INTO media (id, site_id, uploader_id, filename, original_filename, mime_type, file_size, storage_path, storage_backend, alt_text, title, description, width, height, duration, metadata, created_at, updated_at)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18)
+            INSERT INTO media (id, site_id, uploader_id, filename, original_filename, mime_type, file_size, storage_path, storage_backend, alt_text, title, description, width, height, duration, metadata, created_at, updated_at, folder_id)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19)
             RETURNING *
             "#,
         )
@@ -475,6 +543,7 @@ impl MediaService {
         .bind(&media.metadata)
         .bind(media.created_at)
         .bind(media.updated_at)
+        .bind(media.folder_id)
         .fetch_one(&self.pool)
         .await
         .map_err(|e| Error::database_with_source("Failed to create media", e))
@@ -688,6 +757,13 @@ impl MediaService {
         if existing.is_system {
             return Err(Error::validation("Cannot modify system folders"));
         }
+        if let Some(Some(new_parent)) = parent_id {
+            if self.is_in_folder(new_parent, id).await? {
+                return Err(Error::validation(
+                    "Cannot move a folder into itself or one of its subfolders",
+                ));
+            }
+        }
 
         let new_name = name.unwrap_or(existing.name);
         let new_slug = slugify(&new_name);
@@ -741,6 +817,27 @@ impl MediaService {
         })
     }
 
+    /// Whether `folder` is `ancestor` or nested anywhere below it
+    async fn is_in_folder(&self, folder: Uuid, ancestor: Uuid) -> Result<bool> {
+        let query = r#"
+            WITH RECURSIVE ancestors AS (
+                SELECT id, parent_id FROM media_folders WHERE id = $1
+                UNION
+                SELECT mf.id, mf.parent_id FROM media_folders mf
+                JOIN ancestors a ON mf.id = a.parent_id
+            )
+            SELECT EXISTS (SELECT 1 FROM ancestors WHERE id = $2)
+        "#;
+
+        let (found,): (bool,) = sqlx::query_as(query)
+            .bind(folder)
+            .bind(ancestor)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to check folder nesting", e))?;
+        Ok(found)
+    }
+
     /// Delete a folder
     pub async fn delete_folder(&self, id: Uuid, move_contents_to: Option<Uuid>) -> Result<bool> {
         let folder = self
@@ -793,12 +890,21 @@ impl MediaService {
 
     /// Move multiple media items to a folder
     pub async fn bulk_move(&self, media_ids: Vec<Uuid>, folder_id: Option<Uuid>) -> Result<usize> {
+        check_bulk_size(&media_ids)?;
         if media_ids.is_empty() {
             return Ok(0);
         }
+        if let Some(folder_id) = folder_id {
+            self.get_folder(folder_id)
+                .await?
+                .ok_or_else(|| Error::not_found("Folder", folder_id.to_string()))?;
+        }
 
-        let query = "UPDATE media SET folder_id = $1, updated_at = NOW() WHERE id = ANY($2)";
-        let result = sqlx::query(query)
+        let query = format!(
+            "UPDATE media SET folder_id = $1, updated_at = NOW() WHERE id = ANY($2) AND {}",
+            self.site_condition()
+        );
+        let result = sqlx::query(&query)
             .bind(folder_id)
             .bind(&media_ids)
             .execute(&self.pool)
@@ -814,6 +920,7 @@ impl MediaService {
         media_ids: Vec<Uuid>,
         permanent: bool,
     ) -> Result<BulkDeleteResponse> {
+        check_bulk_size(&media_ids)?;
         let mut deleted = Vec::new();
         let mut failed = Vec::new();
 
@@ -840,6 +947,18 @@ impl MediaService {
 
     /// Permanently delete a media item
     pub async fn permanent_delete_media(&self, id: Uuid) -> Result<bool> {
+        let exists: bool = sqlx::query_scalar(&format!(
+            "SELECT EXISTS (SELECT 1 FROM media WHERE id = $1 AND {})",
+            self.site_condition()
+        ))
+        .bind(id)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to get media", e))?;
+        if !exists {
+            return Err(Error::not_found("Media", id.to_string()));
+        }
+
         // Delete variants first
         sqlx::query("DELETE FROM media_variants WHERE media_id = $1")
             .bind(id)
@@ -871,6 +990,7 @@ impl MediaService {
         add_tags: Option<Vec<String>>,
         remove_tags: Option<Vec<String>>,
     ) -> Result<usize> {
+        check_bulk_size(&media_ids)?;
         if media_ids.is_empty() {
             return Ok(0);
         }
@@ -880,13 +1000,19 @@ impl MediaService {
         // Add tags
         if let Some(tags_to_add) = add_tags {
             if !tags_to_add.is_empty() {
-                let query = r#"
+                let query = format!(
+                    r#"
                     UPDATE media SET
-                        tags = array_cat(COALESCE(tags, ARRAY[]::TEXT[]), $1::TEXT[]),
+                        tags = ARRAY(
+                            SELECT DISTINCT tag FROM unnest(array_cat(tags, $1::TEXT[])) AS tag
+                            ORDER BY tag
+                        ),
                         updated_at = NOW()
-                    WHERE id = ANY($2)
-                "#;
-                let result = sqlx::query(query)
+                    WHERE id = ANY($2) AND {}
+                "#,
+                    self.site_condition()
+                );
+                let result = sqlx::query(&query)
                     .bind(&tags_to_add)
                     .bind(&media_ids)
                     .execute(&self.pool)
@@ -899,13 +1025,19 @@ impl MediaService {
         // Remove tags
         if let Some(tags_to_remove) = remove_tags {
             if !tags_to_remove.is_empty() {
-                let query = r#"
+                let query = format!(
+                    r#"
                     UPDATE media SET
-                        tags = array_remove_all(COALESCE(tags, ARRAY[]::TEXT[]), $1::TEXT[]),
+                        tags = ARRAY(
+                            SELECT tag FROM unnest(tags) AS tag
+                            WHERE tag <> ALL($1::TEXT[])
+                        ),
                         updated_at = NOW()
-                    WHERE id = ANY($2)
-                "#;
-                let result = sqlx::query(query)
+                    WHERE id = ANY($2) AND {}
+                "#,
+                    self.site_condition()
+                );
+                let result = sqlx::query(&query)
                     .bind(&tags_to_remove)
                     .bind(&media_ids)
                     .execute(&self.pool)
@@ -1072,7 +1204,7 @@ impl MediaService {
             .bind(media_id)
             .bind(&entity_type)
             .bind(entity_id)
-            .bind(&context)
+            .bind(context.as_deref().unwrap_or(USAGE_CONTENT))
             .execute(&self.pool)
             .await
             .map_err(|e| Error::database_with_source("Failed to track usage", e))?;
@@ -1106,8 +1238,7 @@ impl MediaService {
         let query = r#"
             SELECT mu.id, mu.media_id, mu.entity_type, mu.entity_id, mu.context, mu.created_at,
                    CASE
-                       WHEN mu.entity_type = 'post' THEN (SELECT title FROM posts WHERE id = mu.entity_id)
-                       WHEN mu.entity_type = 'page' THEN (SELECT title FROM pages WHERE id = mu.entity_id)
+                       WHEN mu.entity_type IN ('post', 'page') THEN (SELECT title FROM posts WHERE id = mu.entity_id)
                        ELSE NULL
                    END as entity_title
             FROM media_usage mu
@@ -1135,25 +1266,110 @@ impl MediaService {
             .collect())
     }
 
+    /// Replace the usage records of a post or page with the media its
+    /// content and featured image reference now
+    pub async fn sync_usage(
+        &self,
+        entity_type: &str,
+        entity_id: Uuid,
+        content: &str,
+        featured_image_id: Option<Uuid>,
+    ) -> Result<()> {
+        let references = media_references(content);
+
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| Error::database_with_source("Failed to begin transaction", e))?;
+
+        sqlx::query(
+            "DELETE FROM media_usage WHERE entity_type = $1 AND entity_id = $2 AND context = ANY($3)",
+        )
+        .bind(entity_type)
+        .bind(entity_id)
+        .bind([USAGE_CONTENT, USAGE_FEATURED])
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to clear usage", e))?;
+
+        // Only media that exists is recorded; other /uploads/ links are
+        // left alone
+        sqlx::query(&format!(
+            r#"
+            INSERT INTO media_usage (media_id, entity_type, entity_id, context)
+            SELECT id, $1, $2, $3 FROM media
+            WHERE (id = ANY($4) OR storage_path = ANY($5)) AND {}
+            ON CONFLICT (media_id, entity_type, entity_id, context) DO NOTHING
+            "#,
+            self.site_condition()
+        ))
+        .bind(entity_type)
+        .bind(entity_id)
+        .bind(USAGE_CONTENT)
+        .bind(&references.ids)
+        .bind(&references.paths)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to track usage", e))?;
+
+        if let Some(featured_image_id) = featured_image_id {
+            sqlx::query(&format!(
+                r#"
+                INSERT INTO media_usage (media_id, entity_type, entity_id, context)
+                SELECT id, $1, $2, $3 FROM media WHERE id = $4 AND {}
+                ON CONFLICT (media_id, entity_type, entity_id, context) DO NOTHING
+                "#,
+                self.site_condition()
+            ))
+            .bind(entity_type)
+            .bind(entity_id)
+            .bind(USAGE_FEATURED)
+            .bind(featured_image_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to track usage", e))?;
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| Error::database_with_source("Failed to track usage", e))
+    }
+
+    /// Drop every usage record of a deleted post or page
+    pub async fn clear_usage(&self, entity_type: &str, entity_id: Uuid) -> Result<()> {
+        sqlx::query("DELETE FROM media_usage WHERE entity_type = $1 AND entity_id = $2")
+            .bind(entity_type)
+            .bind(entity_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to clear usage", e))?;
+        Ok(())
+    }
+
     // =====================
     // FAVORITES & PREFERENCES
     // =====================
 
     /// Toggle favorite status
     pub async fn toggle_favorite(&self, media_id: Uuid) -> Result<bool> {
-        let query = r#"
+        let query = format!(
+            r#"
             UPDATE media SET
                 is_favorite = NOT COALESCE(is_favorite, FALSE),
                 updated_at = NOW()
-            WHERE id = $1
+            WHERE id = $1 AND {}
             RETURNING is_favorite
-        "#;
+        "#,
+            self.site_condition()
+        );
 
-        let (is_favorite,): (bool,) = sqlx::query_as(query)
+        let (is_favorite,): (bool,) = sqlx::query_as(&query)
             .bind(media_id)
-            .fetch_one(&self.pool)
+            .fetch_optional(&self.pool)
             .await
-            .map_err(|e| Error::database_with_source("Failed to toggle favorite", e))?;
+            .map_err(|e| Error::database_with_source("Failed to toggle favorite", e))?
+            .ok_or_else(|| Error::not_found("Media", media_id.to_string()))?;
 
         Ok(is_favorite)
     }
@@ -1289,7 +1505,8 @@ impl MediaService {
 
     /// Get media statistics
     pub async fn get_media_stats(&self) -> Result<MediaStats> {
-        let query = r#"
+        let query = format!(
+            r#"
             SELECT
                 COUNT(*) as total_items,
                 COALESCE(SUM(file_size), 0) as total_size,
@@ -1301,8 +1518,10 @@ impl MediaService {
                 COALESCE(SUM(CASE WHEN is_optimized THEN original_size - file_size ELSE 0 END), 0) as total_savings,
                 COUNT(*) FILTER (WHERE is_favorite = TRUE) as favorites_count
             FROM media
-            WHERE deleted_at IS NULL
-        "#;
+            WHERE deleted_at IS NULL AND {}
+        "#,
+            self.site_condition()
+        );
 
         let (
             total_items,
@@ -1314,7 +1533,7 @@ impl MediaService {
             optimized_count,
             total_savings,
             favorites_count,
-        ): (i64, i64, i64, i64, i64, i64, i64, i64, i64) = sqlx::query_as(query)
+        ): (i64, i64, i64, i64, i64, i64, i64, i64, i64) = sqlx::query_as(&query)
             .fetch_one(&self.pool)
             .await
             .map_err(|e| Error::database_with_source("Failed to get media stats", e))?;
@@ -1376,8 +1595,11 @@ impl MediaService {
 
     /// Increment view count
     pub async fn increment_view(&self, media_id: Uuid) -> Result<()> {
-        let query = "UPDATE media SET view_count = COALESCE(view_count, 0) + 1 WHERE id = $1";
-        sqlx::query(query)
+        let query = format!(
+            "UPDATE media SET view_count = COALESCE(view_count, 0) + 1 WHERE id = $1 AND {}",
+            self.site_condition()
+        );
+        sqlx::query(&query)
             .bind(media_id)
             .execute(&self.pool)
             .await
@@ -1387,9 +1609,12 @@ impl MediaService {
 
     /// Increment download count
     pub async fn increment_download(&self, media_id: Uuid) -> Result<()> {
-        let query =
-            "UPDATE media SET download_count = COALESCE(download_count, 0) + 1 WHERE id = $1";
-        sqlx::query(query)
+        let query = format!(
+            "UPDATE media SET download_count = COALESCE(download_count, 0) + 1 \
+             WHERE id = $1 AND {}",
+            self.site_condition()
+        );
+        sqlx::query(&query)
             .bind(media_id)
             .execute(&self.pool)
             .await
@@ -1458,6 +1683,64 @@ struct PreferencesRow {
     allowed_types: Vec<String>,
 }
 
+/// Most media items one bulk request may touch
+pub const MAX_BULK_ITEMS: usize = 500;
+
+fn check_bulk_size(media_ids: &[Uuid]) -> Result<()> {
+    if media_ids.len() > MAX_BULK_ITEMS {
+        return Err(Error::validation(format!(
+            "At most {} media items can be changed at once",
+            MAX_BULK_ITEMS
+        )));
+    }
+    Ok(())
+}
+
+/// Usage context of media referenced in the content body
+pub const USAGE_CONTENT: &str = "content";
+
+/// Usage context of a featured image
+pub const USAGE_FEATURED: &str = "featured";
+
+/// Media referenced by a piece of content
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct MediaReferences {
+    /// IDs from `data-media-id` attributes
+    pub ids: Vec<Uuid>,
+    /// Storage paths from `/uploads/...` URLs
+    pub paths: Vec<String>,
+}
+
+/// Find the media HTML content links to or embeds
+pub fn media_references(content: &str) -> MediaReferences {
+    const ID_ATTRIBUTE: &str = "data-media-id=";
+    const UPLOADS: &str = "/uploads/";
+
+    let mut references = MediaReferences::default();
+
+    for (start, _) in content.match_indices(ID_ATTRIBUTE) {
+        let value = content[start + ID_ATTRIBUTE.len()..].trim_start_matches(['"', '\'']);
+        if let Some(id) = value.get(..36).and_then(|id| Uuid::parse_str(id).ok()) {
+            if !references.ids.contains(&id) {
+                references.ids.push(id);
+            }
+        }
+    }
+
+    for (start, _) in content.match_indices(UPLOADS) {
+        let rest = &content[start + UPLOADS.len()..];
+        let end = rest
+            .find(|c: char| c.is_whitespace() || "\"'()<>?#,".contains(c))
+            .unwrap_or(rest.len());
+        let path = &rest[..end];
+        if !path.is_empty() && !references.paths.iter().any(|p| p == path) {
+            references.paths.push(path.to_string());
+        }
+    }
+
+    references
+}
+
 /// Generate a URL-friendly slug from a string
 fn slugify(text: &str) -> String {
     text.to_lowercase()
@@ -1542,6 +1825,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_media_references() {
+        let id = Uuid::new_v4();
+        let content = format!(
+            r#"<figure data-media-id="{id}"><img src="https://example.com/uploads/2024/01/02/a.jpg?w=300" srcset="/uploads/2024/01/02/a.jpg 1x, /uploads/2024/01/02/b.jpg 2x"></figure>
+            <a href='/uploads/docs/report.pdf'>Report</a> <img data-media-id="not-a-uuid">"#
+        );
+
+        let references = media_references(&content);
+        assert_eq!(references.ids, vec![id]);
+        assert_eq!(
+            references.paths,
+            vec!["2024/01/02/a.jpg", "2024/01/02/b.jpg", "docs/report.pdf"]
+        );
+        assert_eq!(
+            media_references("<p>No media</p>"),
+            MediaReferences::default()
+        );
+    }
+
     #[test]
     fn test_validate_upload() {
         assert!(validate_upload("test.jpg", 1000).is_ok());
@@ -3,6 +3,7 @@
 use chrono::{DateTime, Utc};
 use rustpress_core::error::{Error, Result};
 use rustpress_core::service::SortOrder;
+use rustpress_core::tenant::current_site;
 use rustpress_database::models::PageRow;
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
@@ -169,11 +170,11 @@ pub struct PageService {
 }
 
 impl PageService {
-    /// Create a new page service
+    /// Create a new page service for the site the current request serves
     pub fn new(pool: PgPool) -> Self {
         Self {
             pool,
-            site_id: None,
+            site_id: current_site(),
         }
     }
 
@@ -533,9 +534,10 @@ impl PageService {
                 canonical_url = $15,
                 published_at = $16,
                 updated_at = NOW()
-            WHERE id = $1 AND post_type = 'page'
+            WHERE id = $1 AND {} AND post_type = 'page'
             RETURNING {}
             "#,
+            self.site_condition(),
             PageRow::COLUMNS
         );
         sqlx::query_as::<_, PageRow>(&query)
@@ -561,11 +563,15 @@ impl PageService {
     }
 
     async fn soft_delete(&self, id: Uuid) -> Result<()> {
-        sqlx::query("UPDATE posts SET deleted_at = NOW(), updated_at = NOW() WHERE id = $1 AND post_type = 'page'")
-            .bind(id)
-            .execute(&self.pool)
-            .await
-            .map_err(|e| Error::database_with_source("Failed to delete page", e))?;
+        sqlx::query(&format!(
+            "UPDATE posts SET deleted_at = NOW(), updated_at = NOW() \
+             WHERE id = $1 AND {} AND post_type = 'page'",
+            self.site_condition()
+        ))
+        .bind(id)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to delete page", e))?;
         Ok(())
     }
 
@@ -1,12 +1,19 @@
 //! Post service for handling post-related business logic.
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use rustpress_admin::functions::EventDispatcher;
 use rustpress_core::error::{Error, Result};
 use rustpress_core::service::{ListParams, SortOrder};
+use rustpress_core::tenant::current_site;
+use rustpress_database::filter::POST_FILTERS;
 use rustpress_database::repository::posts::{PostRepository, PostRow};
-use serde::{Deserialize, Serialize};
+use rustpress_editor::post::{
+    day_start, local_date, EmbargoWindow, PostPublishing, PublishStatus, ScheduleError,
+    ScheduleTime,
+};
+use serde::{Deserialize, Deserializer, Serialize};
 use sqlx::PgPool;
+use std::collections::HashMap;
 use uuid::Uuid;
 
 /// Post status enum
@@ -79,8 +86,17 @@ pub struct PostResponse {
     pub comment_status: Option<String>,
     pub ping_status: Option<String>,
     pub published_at: Option<DateTime<Utc>>,
+    /// When a scheduled post goes live
+    pub scheduled_at: Option<DateTime<Utc>>,
+    /// When the post goes back to draft
+    pub expires_at: Option<DateTime<Utc>>,
+    pub embargo_starts_at: Option<DateTime<Utc>>,
+    pub embargo_ends_at: Option<DateTime<Utc>>,
+    /// IANA timezone the schedule was entered in
+    pub timezone: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub version: i64,
     pub categories: Vec<TermResponse>,
     pub tags: Vec<TermResponse>,
 }
@@ -120,6 +136,17 @@ pub struct CreatePostRequest {
     pub published_at: Option<DateTime<Utc>>,
     pub category_ids: Option<Vec<Uuid>>,
     pub tag_ids: Option<Vec<Uuid>>,
+    /// Publish date; a future one schedules the post. Dates without an
+    /// offset are read in `timezone`.
+    pub scheduled_at: Option<ScheduleTime>,
+    /// When the post goes back to draft
+    pub expires_at: Option<ScheduleTime>,
+    /// Start of the period the post is withheld in; right away if only the
+    /// end is given
+    pub embargo_starts_at: Option<ScheduleTime>,
+    pub embargo_ends_at: Option<ScheduleTime>,
+    /// IANA timezone, e.g. `Europe/Berlin`
+    pub timezone: Option<String>,
 }
 
 /// Update post request
@@ -139,6 +166,251 @@ pub struct UpdatePostRequest {
     pub published_at: Option<DateTime<Utc>>,
     pub category_ids: Option<Vec<Uuid>>,
     pub tag_ids: Option<Vec<Uuid>>,
+    /// Version the client last read; the update is rejected with a
+    /// conflict if the post has changed since
+    #[serde(default)]
+    pub version: Option<i64>,
+    /// Publish date; a future one schedules the post. Dates without an
+    /// offset are read in `timezone`.
+    pub scheduled_at: Option<ScheduleTime>,
+    /// When the post goes back to draft; `null` clears it
+    #[serde(default, deserialize_with = "nullable")]
+    pub expires_at: Option<Option<ScheduleTime>>,
+    /// `null` clears it
+    #[serde(default, deserialize_with = "nullable")]
+    pub embargo_starts_at: Option<Option<ScheduleTime>>,
+    /// `null` lifts the embargo
+    #[serde(default, deserialize_with = "nullable")]
+    pub embargo_ends_at: Option<Option<ScheduleTime>>,
+    /// IANA timezone, e.g. `Europe/Berlin`
+    pub timezone: Option<String>,
+}
+
+/// Tell a field set to `null` (`Some(None)`) from one left out (`None`)
+fn nullable<'de, D, T>(deserializer: D) -> std::result::Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// Requested changes to a post's status and schedule. Absent fields keep
+/// their current value.
+#[derive(Debug, Clone, Default)]
+struct ScheduleChange {
+    status: Option<String>,
+    timezone: Option<String>,
+    scheduled_at: Option<ScheduleTime>,
+    published_at: Option<DateTime<Utc>>,
+    expires_at: Option<Option<ScheduleTime>>,
+    embargo_starts_at: Option<Option<ScheduleTime>>,
+    embargo_ends_at: Option<Option<ScheduleTime>>,
+}
+
+/// Publishing state of a stored post
+fn publishing_from_row(row: &PostRow) -> PostPublishing {
+    PostPublishing {
+        status: row.status.parse().unwrap_or_default(),
+        scheduled_at: row.scheduled_at,
+        published_at: row.published_at,
+        expires_at: row.expires_at,
+        embargo: row.embargo_ends_at.map(|ends_at| EmbargoWindow {
+            starts_at: row.embargo_starts_at,
+            ends_at,
+        }),
+        timezone: row.schedule_timezone.clone(),
+        ..Default::default()
+    }
+}
+
+fn schedule_error(field: &str, error: ScheduleError) -> Error {
+    Error::invalid_input(field, error.to_string())
+}
+
+/// Work out a post's status and dates from its current state and the
+/// requested changes. Publishing with a future date schedules the post, an
+/// embargo holds publication back until it ends, and the expiry must come
+/// after the post goes live.
+fn resolve_schedule(
+    existing: Option<&PostRow>,
+    change: ScheduleChange,
+    now: DateTime<Utc>,
+) -> Result<PostPublishing> {
+    let mut publishing = existing.map(publishing_from_row).unwrap_or_default();
+    let old_status = publishing.status;
+    let status = match change.status.as_deref() {
+        Some(status) => status
+            .parse::<PublishStatus>()
+            .map_err(|e| Error::invalid_input("status", e))?,
+        // A publish date alone schedules the post
+        None if change.scheduled_at.is_some() => PublishStatus::Scheduled,
+        None => old_status,
+    };
+
+    if let Some(timezone) = change.timezone.as_deref() {
+        publishing
+            .set_timezone(Some(timezone))
+            .map_err(|e| schedule_error("timezone", e))?;
+    }
+    let resolve = |field: &str, time: Option<ScheduleTime>| {
+        time.map(|time| publishing.resolve(time))
+            .transpose()
+            .map_err(|e| schedule_error(field, e))
+    };
+    let scheduled_at = resolve("scheduled_at", change.scheduled_at)?;
+    let expires_at = match change.expires_at {
+        Some(time) => resolve("expires_at", time)?,
+        None => publishing.expires_at,
+    };
+    let embargo_starts_at = match change.embargo_starts_at {
+        Some(time) => resolve("embargo_starts_at", time)?,
+        None => publishing.embargo.and_then(|embargo| embargo.starts_at),
+    };
+    let embargo = match change.embargo_ends_at {
+        Some(time) => resolve("embargo_ends_at", time)?,
+        None => publishing.embargo.map(|embargo| embargo.ends_at),
+    }
+    .map(|ends_at| EmbargoWindow::new(embargo_starts_at, ends_at))
+    .transpose()
+    .map_err(|e| schedule_error("embargo_ends_at", e))?;
+    if embargo.is_none() && matches!(change.embargo_starts_at, Some(Some(_))) {
+        return Err(Error::invalid_input(
+            "embargo_ends_at",
+            "An embargo needs an end",
+        ));
+    }
+    let embargo_changed = embargo != publishing.embargo;
+    publishing.embargo = embargo;
+
+    match status {
+        PublishStatus::Published | PublishStatus::Scheduled => {
+            if status != old_status
+                || scheduled_at.is_some()
+                || change.published_at.is_some()
+                || embargo_changed
+            {
+                let current = match (old_status, status) {
+                    (PublishStatus::Scheduled, PublishStatus::Scheduled) => publishing.scheduled_at,
+                    (_, PublishStatus::Published) => publishing.published_at,
+                    _ => None,
+                };
+                let at = scheduled_at
+                    .or(change.published_at)
+                    .or(current)
+                    .or((status == PublishStatus::Published).then_some(now))
+                    .ok_or_else(|| {
+                        Error::invalid_input("scheduled_at", "Scheduling a post needs a date")
+                    })?;
+                publishing.publish_at(at, now);
+            }
+        }
+        status => {
+            // Drafts keep the date they are planned for
+            let planned = matches!(
+                status,
+                PublishStatus::Draft | PublishStatus::AutoDraft | PublishStatus::Pending
+            );
+            publishing.scheduled_at = scheduled_at.or(publishing.scheduled_at).filter(|_| planned);
+            publishing.status = status;
+            if change.published_at.is_some() {
+                publishing.published_at = change.published_at;
+            }
+        }
+    }
+
+    publishing
+        .set_expiry(expires_at)
+        .map_err(|e| schedule_error("expires_at", e))?;
+    Ok(publishing)
+}
+
+/// Longest range the editorial calendar shows at once
+pub const MAX_CALENDAR_DAYS: i64 = 100;
+
+/// Most posts the editorial calendar lists per request
+const MAX_CALENDAR_ENTRIES: i64 = 1000;
+
+/// Most undated drafts listed beside the calendar
+const MAX_UNSCHEDULED_ENTRIES: i64 = 100;
+
+/// Statuses shown on the calendar unless asked otherwise
+const CALENDAR_STATUSES: [&str; 4] = ["draft", "pending", "scheduled", "published"];
+
+/// Where a post sits on the calendar; matches [`PostPublishing::calendar_date`]
+const CALENDAR_DATE: &str = "(CASE WHEN status IN ('published', 'private') THEN published_at \
+     WHEN status = 'trash' THEN NULL ELSE scheduled_at END)";
+
+/// Editorial calendar query parameters
+#[derive(Debug, Clone, Deserialize)]
+pub struct CalendarParams {
+    /// First day shown
+    pub from: NaiveDate,
+    /// Last day shown
+    pub to: NaiveDate,
+    /// IANA timezone the days are in; UTC by default
+    pub timezone: Option<String>,
+    pub author_id: Option<Uuid>,
+    pub category_id: Option<Uuid>,
+    /// Comma-separated statuses; drafts, pending, scheduled and published
+    /// posts by default
+    pub status: Option<String>,
+    /// Also list drafts without a planned date
+    #[serde(default)]
+    pub unscheduled: bool,
+}
+
+/// A post on the editorial calendar
+#[derive(Debug, Clone, Serialize)]
+pub struct CalendarEntry {
+    pub id: Uuid,
+    pub title: String,
+    pub slug: String,
+    pub status: String,
+    pub author_id: Uuid,
+    pub author_name: Option<String>,
+    /// Day the post sits on, in the calendar's timezone
+    pub date: Option<NaiveDate>,
+    /// Publish date, or the planned date of a draft
+    pub at: Option<DateTime<Utc>>,
+    pub category_ids: Vec<Uuid>,
+    /// Version to send back when moving the post
+    pub version: i64,
+}
+
+/// Editorial calendar for a range of days
+#[derive(Debug, Clone, Serialize)]
+pub struct CalendarResponse {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub timezone: String,
+    pub entries: Vec<CalendarEntry>,
+    /// Drafts without a planned date, most recently changed first
+    pub unscheduled: Vec<CalendarEntry>,
+}
+
+/// Move a post to another date on the editorial calendar
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReschedulePostRequest {
+    /// Day to move the post to, keeping its time of day
+    pub date: Option<NaiveDate>,
+    /// Exact date and time instead; without an offset it is read in
+    /// `timezone`
+    pub at: Option<ScheduleTime>,
+    /// IANA timezone of the calendar; the post's own timezone by default
+    pub timezone: Option<String>,
+    /// Version the client last read; the move is rejected with a conflict
+    /// if the post has changed since
+    #[serde(default)]
+    pub version: Option<i64>,
+}
+
+/// A post moved on the editorial calendar
+#[derive(Debug, Clone, Serialize)]
+pub struct RescheduledPost {
+    pub post: PostResponse,
+    /// Where the post sat before
+    pub previous_at: Option<DateTime<Utc>>,
 }
 
 /// Post list query parameters
@@ -151,6 +423,8 @@ pub struct PostListParams {
     pub search: Option<String>,
     pub sort_by: Option<String>,
     pub sort_order: Option<String>,
+    /// Filter expression, e.g. `status:published AND category:rust`
+    pub filter: Option<String>,
 }
 
 impl From<PostRow> for PostResponse {
@@ -171,8 +445,14 @@ impl From<PostRow> for PostResponse {
             comment_status: Some(row.comment_status),
             ping_status: Some(row.ping_status),
             published_at: row.published_at,
+            scheduled_at: row.scheduled_at,
+            expires_at: row.expires_at,
+            embargo_starts_at: row.embargo_starts_at,
+            embargo_ends_at: row.embargo_ends_at,
+            timezone: row.schedule_timezone,
             created_at: row.created_at,
             updated_at: row.updated_at,
+            version: row.version,
             categories: vec![],
             tags: vec![],
         }
@@ -188,12 +468,12 @@ pub struct PostService {
 }
 
 impl PostService {
-    /// Create a new post service
+    /// Create a new post service for the site the current request serves
     pub fn new(pool: PgPool) -> Self {
         let dispatcher = EventDispatcher::new(pool.clone());
         Self {
             pool,
-            site_id: None,
+            site_id: current_site(),
             dispatcher,
         }
     }
@@ -235,11 +515,21 @@ impl PostService {
             return Err(Error::validation("A post with this slug already exists"));
         }
 
-        let status = request
-            .status
-            .clone()
-            .unwrap_or_else(|| "draft".to_string());
         let now = Utc::now();
+        let publishing = resolve_schedule(
+            None,
+            ScheduleChange {
+                status: request.status.clone(),
+                timezone: request.timezone.clone(),
+                scheduled_at: request.scheduled_at,
+                published_at: request.published_at,
+                expires_at: Some(request.expires_at),
+                embargo_starts_at: Some(request.embargo_starts_at),
+                embargo_ends_at: Some(request.embargo_ends_at),
+            },
+            now,
+        )?;
+        let status = publishing.status.as_str().to_string();
 
         // Prepare event data for hooks
         let event_data = serde_json::json!({
@@ -290,13 +580,6 @@ impl PostService {
             .map(|s| s.to_string())
             .or(request.excerpt.clone());
 
-        // Set published_at if status is published and not already set
-        let published_at = if status == "published" {
-            request.published_at.or(Some(now))
-        } else {
-            request.published_at
-        };
-
         let post = PostRow {
             id: Uuid::now_v7(),
             site_id: self.site_id,
@@ -319,11 +602,16 @@ impl PostService {
             meta_title: None,
             meta_description: None,
             canonical_url: None,
-            published_at,
-            scheduled_at: None,
+            published_at: publishing.published_at,
+            scheduled_at: publishing.scheduled_at,
+            expires_at: publishing.expires_at,
+            embargo_starts_at: publishing.embargo.and_then(|embargo| embargo.starts_at),
+            embargo_ends_at: publishing.embargo.map(|embargo| embargo.ends_at),
+            schedule_timezone: publishing.timezone,
             created_at: now,
             updated_at: now,
             deleted_at: None,
+            version: 1,
         };
 
         let created = self.repo().create(&post).await?;
@@ -412,6 +700,8 @@ impl PostService {
             ));
         }
 
+        conditions.extend(POST_FILTERS.parse_conditions(params.filter.as_deref())?);
+
         let where_clause = conditions.join(" AND ");
         let order_by = params.sort_by.as_deref().unwrap_or("created_at");
         let order_dir = if sort_order == SortOrder::Desc {
@@ -504,6 +794,18 @@ impl PostService {
             .await?
             .ok_or_else(|| Error::not_found("Post", id.to_string()))?;
 
+        // Reject stale edits before running hooks; the repository re-checks
+        // the version atomically on write
+        if let Some(expected) = request.version {
+            if expected != existing.version {
+                return Err(Error::version_conflict(
+                    "Post",
+                    id.to_string(),
+                    existing.version,
+                ));
+            }
+        }
+
         // Check slug uniqueness if changed
         if let Some(ref new_slug) = request.slug {
             if new_slug != &existing.slug {
@@ -513,8 +815,22 @@ impl PostService {
             }
         }
 
+        let publishing = resolve_schedule(
+            Some(&existing),
+            ScheduleChange {
+                status: request.status.clone(),
+                timezone: request.timezone.clone(),
+                scheduled_at: request.scheduled_at,
+                published_at: request.published_at,
+                expires_at: request.expires_at,
+                embargo_starts_at: request.embargo_starts_at,
+                embargo_ends_at: request.embargo_ends_at,
+            },
+            Utc::now(),
+        )?;
+
         let was_published = existing.status == "published";
-        let new_status = request.status.as_ref().unwrap_or(&existing.status);
+        let new_status = publishing.status.as_str();
         let is_publishing = !was_published && new_status == "published";
 
         // Prepare event data for BEFORE hooks
@@ -571,13 +887,6 @@ impl PostService {
             .or(request.excerpt.clone())
             .or(existing.excerpt.clone());
 
-        // Set published_at if publishing for the first time
-        let published_at = if is_publishing && existing.published_at.is_none() {
-            Some(Utc::now())
-        } else {
-            request.published_at.or(existing.published_at)
-        };
-
         let updated_post = PostRow {
             id: existing.id,
             site_id: existing.site_id,
@@ -587,7 +896,7 @@ impl PostService {
             slug: final_slug,
             content: final_content,
             excerpt: final_excerpt,
-            status: request.status.clone().unwrap_or(existing.status),
+            status: new_status.to_string(),
             visibility: request.visibility.unwrap_or(existing.visibility),
             password: request.password.or(existing.password),
             parent_id: existing.parent_id,
@@ -600,11 +909,16 @@ impl PostService {
             meta_title: existing.meta_title,
             meta_description: existing.meta_description,
             canonical_url: existing.canonical_url,
-            published_at,
-            scheduled_at: existing.scheduled_at,
+            published_at: publishing.published_at,
+            scheduled_at: publishing.scheduled_at,
+            expires_at: publishing.expires_at,
+            embargo_starts_at: publishing.embargo.and_then(|embargo| embargo.starts_at),
+            embargo_ends_at: publishing.embargo.map(|embargo| embargo.ends_at),
+            schedule_timezone: publishing.timezone.clone(),
             created_at: existing.created_at,
             updated_at: Utc::now(),
             deleted_at: existing.deleted_at,
+            version: request.version.unwrap_or(existing.version),
         };
 
         let updated = self.repo().update(&updated_post).await?;
@@ -747,6 +1061,13 @@ impl PostService {
             .await?
             .ok_or_else(|| Error::not_found("Post", id.to_string()))?;
 
+        if publishing_from_row(&existing).is_embargoed(Utc::now()) {
+            return Err(Error::validation(format!(
+                "The post is embargoed until {}",
+                existing.embargo_ends_at.unwrap_or_default().to_rfc3339()
+            )));
+        }
+
         // Prepare event data for BEFORE hooks
         let event_data = serde_json::json!({
             "post_id": id.to_string(),
@@ -776,6 +1097,7 @@ impl PostService {
         let updated_post = PostRow {
             status: "published".to_string(),
             published_at,
+            scheduled_at: None,
             updated_at: Utc::now(),
             ..existing
         };
@@ -883,6 +1205,241 @@ impl PostService {
         Ok(response)
     }
 
+    /// Posts on the editorial calendar between two days, by date
+    pub async fn calendar(&self, params: CalendarParams) -> Result<CalendarResponse> {
+        if params.to < params.from {
+            return Err(Error::invalid_input("to", "Must not be before from"));
+        }
+        if (params.to - params.from).num_days() >= MAX_CALENDAR_DAYS {
+            return Err(Error::invalid_input(
+                "to",
+                format!("The calendar shows at most {} days", MAX_CALENDAR_DAYS),
+            ));
+        }
+        let timezone = params.timezone.as_deref().map(str::trim);
+        let start = day_start(params.from, timezone).map_err(|e| schedule_error("timezone", e))?;
+        let end = params
+            .to
+            .succ_opt()
+            .map(|next| day_start(next, timezone))
+            .transpose()
+            .map_err(|e| schedule_error("timezone", e))?
+            .unwrap_or(DateTime::<Utc>::MAX_UTC);
+
+        let statuses = match params.status.as_deref() {
+            Some(status) => status
+                .split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(|s| {
+                    s.parse::<PublishStatus>()
+                        .map(|status| status.as_str().to_string())
+                        .map_err(|e| Error::invalid_input("status", e))
+                })
+                .collect::<Result<Vec<_>>>()?,
+            None => CALENDAR_STATUSES.iter().map(|s| s.to_string()).collect(),
+        };
+
+        let site_condition = match self.site_id {
+            Some(site_id) => format!("site_id = '{}'", site_id),
+            None => "site_id IS NULL".to_string(),
+        };
+        let filter = format!(
+            "post_type IN ('post', 'episode') AND deleted_at IS NULL AND {} AND status = ANY($1) \
+             AND ($2::uuid IS NULL OR author_id = $2) \
+             AND ($3::uuid IS NULL OR EXISTS (SELECT 1 FROM term_relationships tr \
+             WHERE tr.object_id = posts.id AND tr.object_type = 'post' AND tr.term_id = $3))",
+            site_condition
+        );
+
+        let rows: Vec<PostRow> = sqlx::query_as(&format!(
+            "SELECT {} FROM posts WHERE {} AND {date} >= $4 AND {date} < $5 \
+             ORDER BY {date}, id LIMIT {}",
+            PostRow::COLUMNS,
+            filter,
+            MAX_CALENDAR_ENTRIES,
+            date = CALENDAR_DATE
+        ))
+        .bind(&statuses)
+        .bind(params.author_id)
+        .bind(params.category_id)
+        .bind(start)
+        .bind(end)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load calendar", e))?;
+
+        let has_drafts = statuses
+            .iter()
+            .any(|s| matches!(s.as_str(), "draft" | "autodraft" | "pending"));
+        let unscheduled_rows: Vec<PostRow> = if params.unscheduled && has_drafts {
+            sqlx::query_as(&format!(
+                "SELECT {} FROM posts WHERE {} AND status IN ('draft', 'autodraft', 'pending') \
+                 AND scheduled_at IS NULL ORDER BY updated_at DESC LIMIT {}",
+                PostRow::COLUMNS,
+                filter,
+                MAX_UNSCHEDULED_ENTRIES
+            ))
+            .bind(&statuses)
+            .bind(params.author_id)
+            .bind(params.category_id)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to load unscheduled drafts", e))?
+        } else {
+            Vec::new()
+        };
+
+        let ids: Vec<Uuid> = rows
+            .iter()
+            .chain(&unscheduled_rows)
+            .map(|row| row.id)
+            .collect();
+        let author_ids: Vec<Uuid> = rows
+            .iter()
+            .chain(&unscheduled_rows)
+            .map(|row| row.author_id)
+            .collect();
+        let mut categories: HashMap<Uuid, Vec<Uuid>> = HashMap::new();
+        let category_rows: Vec<(Uuid, Uuid)> = sqlx::query_as(
+            "SELECT tr.object_id, tr.term_id FROM term_relationships tr \
+             JOIN terms t ON t.id = tr.term_id JOIN taxonomies tax ON tax.id = t.taxonomy_id \
+             WHERE tr.object_id = ANY($1) AND tr.object_type = 'post' AND tax.slug = 'category' \
+             ORDER BY tr.term_order",
+        )
+        .bind(&ids)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load post categories", e))?;
+        for (post_id, term_id) in category_rows {
+            categories.entry(post_id).or_default().push(term_id);
+        }
+        let authors: HashMap<Uuid, Option<String>> = sqlx::query_as::<_, (Uuid, Option<String>)>(
+            "SELECT id, display_name FROM users WHERE id = ANY($1)",
+        )
+        .bind(&author_ids)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load post authors", e))?
+        .into_iter()
+        .collect();
+
+        let mut entry = |row: PostRow| -> Result<CalendarEntry> {
+            let at = publishing_from_row(&row).calendar_date();
+            let date = at
+                .map(|at| local_date(at, timezone))
+                .transpose()
+                .map_err(|e| schedule_error("timezone", e))?;
+            Ok(CalendarEntry {
+                id: row.id,
+                author_name: authors.get(&row.author_id).cloned().flatten(),
+                category_ids: categories.remove(&row.id).unwrap_or_default(),
+                title: row.title,
+                slug: row.slug,
+                status: row.status,
+                author_id: row.author_id,
+                date,
+                at,
+                version: row.version,
+            })
+        };
+        let entries = rows
+            .into_iter()
+            .map(&mut entry)
+            .collect::<Result<Vec<_>>>()?;
+        let unscheduled = unscheduled_rows
+            .into_iter()
+            .map(&mut entry)
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(CalendarResponse {
+            from: params.from,
+            to: params.to,
+            timezone: timezone.unwrap_or("UTC").to_string(),
+            entries,
+            unscheduled,
+        })
+    }
+
+    /// Move a post to another date on the editorial calendar. Drafts get a
+    /// planned date, scheduled posts a new publish date and published
+    /// posts a new (past) publish date; their status doesn't change.
+    pub async fn reschedule_post(
+        &self,
+        id: Uuid,
+        request: ReschedulePostRequest,
+    ) -> Result<RescheduledPost> {
+        let existing = self
+            .repo()
+            .find_by_id(id)
+            .await?
+            .ok_or_else(|| Error::not_found("Post", id.to_string()))?;
+        if let Some(expected) = request.version {
+            if expected != existing.version {
+                return Err(Error::version_conflict(
+                    "Post",
+                    id.to_string(),
+                    existing.version,
+                ));
+            }
+        }
+
+        let mut publishing = publishing_from_row(&existing);
+        let previous_at = publishing.calendar_date();
+
+        // The calendar's timezone only places the new date; the post keeps
+        // its own
+        let mut calendar = publishing.clone();
+        if let Some(timezone) = request.timezone.as_deref() {
+            calendar
+                .set_timezone(Some(timezone))
+                .map_err(|e| schedule_error("timezone", e))?;
+        }
+        let at = match (request.at, request.date) {
+            (Some(at), None) => calendar.resolve(at).map_err(|e| schedule_error("at", e))?,
+            (None, Some(day)) => calendar
+                .same_time_on(day)
+                .map_err(|e| schedule_error("date", e))?,
+            _ => {
+                return Err(Error::invalid_input(
+                    "date",
+                    "Give either a day or an exact time",
+                ))
+            }
+        };
+        publishing
+            .move_to(at, Utc::now())
+            .map_err(|e| schedule_error("date", e))?;
+
+        let updated_post = PostRow {
+            scheduled_at: publishing.scheduled_at,
+            published_at: publishing.published_at,
+            updated_at: Utc::now(),
+            version: request.version.unwrap_or(existing.version),
+            ..existing
+        };
+        let updated = self.repo().update(&updated_post).await?;
+
+        let mut post = PostResponse::from(updated);
+        post.categories = self.get_post_terms(post.id, "category").await?;
+        post.tags = self.get_post_terms(post.id, "post_tag").await?;
+
+        let after_event_data = serde_json::json!({
+            "post_id": post.id.to_string(),
+            "title": post.title,
+            "status": post.status,
+            "author_id": post.author_id.to_string(),
+            "previous_at": previous_at.map(|at| at.to_rfc3339()),
+            "at": at.to_rfc3339(),
+        });
+        let _ = self
+            .dispatcher
+            .dispatch_after("post_rescheduled", &after_event_data)
+            .await;
+
+        Ok(RescheduledPost { post, previous_at })
+    }
+
     /// Get counts by status
     pub async fn get_counts(&self) -> Result<std::collections::HashMap<String, i64>> {
         let site_condition = match self.site_id {
@@ -1076,4 +1633,56 @@ mod tests {
         );
         assert!("invalid".parse::<PostStatus>().is_err());
     }
+
+    #[test]
+    fn test_resolve_schedule() {
+        let now = "2026-03-01T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let time = |s: &str| Some(s.parse::<ScheduleTime>().unwrap());
+
+        // A local publish date alone schedules the post in its timezone
+        let publishing = resolve_schedule(
+            None,
+            ScheduleChange {
+                timezone: Some("Europe/Berlin".to_string()),
+                scheduled_at: time("2026-03-02T09:00:00"),
+                ..Default::default()
+            },
+            now,
+        )
+        .unwrap();
+        assert_eq!(publishing.status, PublishStatus::Scheduled);
+        assert_eq!(
+            publishing.scheduled_at,
+            Some("2026-03-02T08:00:00Z".parse().unwrap())
+        );
+
+        // Publishing during an embargo waits for its end
+        let publishing = resolve_schedule(
+            None,
+            ScheduleChange {
+                status: Some("published".to_string()),
+                embargo_ends_at: Some(time("2026-03-05T00:00:00Z")),
+                ..Default::default()
+            },
+            now,
+        )
+        .unwrap();
+        assert_eq!(publishing.status, PublishStatus::Scheduled);
+        assert_eq!(
+            publishing.scheduled_at,
+            Some("2026-03-05T00:00:00Z".parse().unwrap())
+        );
+
+        // The expiry must come after the post goes live
+        let result = resolve_schedule(
+            None,
+            ScheduleChange {
+                scheduled_at: time("2026-03-02T09:00:00Z"),
+                expires_at: Some(time("2026-03-02T08:00:00Z")),
+                ..Default::default()
+            },
+            now,
+        );
+        assert!(result.is_err());
+    }
 }
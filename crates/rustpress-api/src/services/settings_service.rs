@@ -1,6 +1,7 @@
 //! Settings service for managing site configuration.
 
 use rustpress_core::error::{Error, Result};
+use rustpress_core::tenant::current_site;
 use rustpress_database::repository::options::{OptionRow, OptionsRepository};
 use sqlx::PgPool;
 use uuid::Uuid;
@@ -9,6 +10,9 @@ use uuid::Uuid;
 #[derive(Debug, Clone, serde::Deserialize)]
 pub struct BatchUpdateRequest {
     pub settings: Vec<SettingUpdate>,
+    /// Why the change is made, shown to approvers of sensitive settings
+    #[serde(default)]
+    pub reason: Option<String>,
 }
 
 /// Single setting update within a batch
@@ -16,6 +20,9 @@ pub struct BatchUpdateRequest {
 pub struct SettingUpdate {
     pub key: String,
     pub value: serde_json::Value,
+    /// Version the client last read, if it wants the update guarded
+    #[serde(default)]
+    pub version: Option<i64>,
 }
 
 /// Setting response for API
@@ -31,6 +38,7 @@ pub struct SettingResponse {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub value_type: Option<String>,
     pub is_system: bool,
+    pub version: i64,
 }
 
 /// Grouped settings response for API
@@ -57,6 +65,7 @@ impl From<OptionRow> for SettingResponse {
             description: row.description,
             value_type: row.value_type,
             is_system: row.is_system,
+            version: row.version,
         }
     }
 }
@@ -69,11 +78,11 @@ pub struct SettingsService {
 }
 
 impl SettingsService {
-    /// Create a new settings service
+    /// Create a new settings service for the site the current request serves
     pub fn new(pool: PgPool) -> Self {
         Self {
             pool,
-            site_id: None,
+            site_id: current_site(),
         }
     }
 
@@ -161,60 +170,55 @@ impl SettingsService {
 
     /// Update a single setting
     pub async fn update(&self, key: &str, value: serde_json::Value) -> Result<SettingResponse> {
+        self.update_versioned(key, value, None).await
+    }
+
+    /// Update a single setting, rejecting the write with a version conflict
+    /// if `expected_version` no longer matches the stored version
+    pub async fn update_versioned(
+        &self,
+        key: &str,
+        value: serde_json::Value,
+        expected_version: Option<i64>,
+    ) -> Result<SettingResponse> {
         // First check if this setting exists
         let existing = self.repo().get_full(key).await?;
 
         match existing {
             Some(option) => {
-                // Update existing option
-                self.repo().set(key, value.clone()).await?;
-
-                // Return updated setting
-                Ok(SettingResponse {
-                    key: key.to_string(),
-                    value,
-                    group: option.option_group,
-                    display_name: option.display_name,
-                    description: option.description,
-                    value_type: option.value_type,
-                    is_system: option.is_system,
-                })
+                // Update existing option, guarded by the version read above
+                let updated = self
+                    .repo()
+                    .set_versioned(key, value, expected_version.or(Some(option.version)))
+                    .await?;
+                Ok(SettingResponse::from(updated))
             }
+            None if expected_version.is_some() => Err(Error::not_found("Setting", key)),
             None => {
                 // Create new custom setting in "general" group
-                self.repo().set(key, value.clone()).await?;
-
-                Ok(SettingResponse {
-                    key: key.to_string(),
-                    value,
-                    group: "general".to_string(),
-                    display_name: None,
-                    description: None,
-                    value_type: None,
-                    is_system: false,
-                })
+                self.repo().set(key, value).await?;
+
+                self.repo()
+                    .get_full(key)
+                    .await?
+                    .map(SettingResponse::from)
+                    .ok_or_else(|| Error::not_found("Setting", key))
             }
         }
     }
 
-    /// Batch update multiple settings
+    /// Batch update multiple settings.
+    ///
+    /// All writes happen in one transaction; if any guarded setting is
+    /// stale the whole batch is rejected with a version conflict.
     pub async fn batch_update(&self, updates: Vec<SettingUpdate>) -> Result<Vec<SettingResponse>> {
-        let updates_vec: Vec<(String, serde_json::Value)> = updates
-            .iter()
-            .map(|u| (u.key.clone(), u.value.clone()))
+        let updates = updates
+            .into_iter()
+            .map(|u| (u.key, u.value, u.version))
             .collect();
 
-        self.repo().batch_update(updates_vec).await?;
-
-        // Return updated settings
-        let mut results = Vec::new();
-        for update in updates {
-            if let Some(setting) = self.get(&update.key).await? {
-                results.push(setting);
-            }
-        }
-
-        Ok(results)
+        let updated = self.repo().batch_update_versioned(updates).await?;
+        Ok(updated.into_iter().map(SettingResponse::from).collect())
     }
 
     /// Delete a setting (only non-system settings)
@@ -8,6 +8,7 @@
 //! - apps: Application files
 
 use rustpress_core::error::{Error, Result};
+use rustpress_core::tenant::current_site;
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
 use uuid::Uuid;
@@ -327,11 +328,11 @@ pub struct StorageService {
 }
 
 impl StorageService {
-    /// Create a new storage service
+    /// Create a new storage service for the site the current request serves
     pub fn new(pool: PgPool) -> Self {
         Self {
             pool,
-            site_id: None,
+            site_id: current_site(),
         }
     }
 
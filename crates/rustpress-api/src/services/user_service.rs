@@ -3,6 +3,7 @@
 use chrono::{DateTime, Utc};
 use rustpress_core::error::{Error, Result};
 use rustpress_core::service::SortOrder;
+use rustpress_database::filter::USER_FILTERS;
 use rustpress_database::repository::users::{UserRepository, UserRow};
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
@@ -161,6 +162,8 @@ pub struct UserListParams {
     pub search: Option<String>,
     pub sort_by: Option<String>,
     pub sort_order: Option<String>,
+    /// Filter expression, e.g. `status:published AND category:rust`
+    pub filter: Option<String>,
 }
 
 impl From<UserRow> for UserResponse {
@@ -298,6 +301,8 @@ impl UserService {
             ));
         }
 
+        conditions.extend(USER_FILTERS.parse_conditions(params.filter.as_deref())?);
+
         let where_clause = conditions.join(" AND ");
         let order_by = params.sort_by.as_deref().unwrap_or("created_at");
         let order_dir = if sort_order == SortOrder::Desc {
@@ -8,7 +8,7 @@ use rustpress_core::error::{Error, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::net::IpAddr;
-use std::sync::RwLock;
+use std::sync::{Arc, RwLock};
 use uuid::Uuid;
 
 /// IP filter rule type
@@ -43,6 +43,19 @@ impl IpRule {
     pub fn matches(&self, ip: &IpAddr) -> bool {
         self.is_active() && self.pattern.matches(ip)
     }
+
+    /// Match, resolving country and ASN patterns through `geo`
+    pub fn matches_with(&self, ip: &IpAddr, geo: Option<&dyn GeoLookup>) -> bool {
+        self.is_active() && self.pattern.matches_with(ip, geo)
+    }
+}
+
+/// Country and ASN resolution for [`IpPattern::Country`] and [`IpPattern::Asn`]
+pub trait GeoLookup: Send + Sync {
+    /// ISO 3166-1 alpha-2 country code
+    fn country_code(&self, ip: &IpAddr) -> Option<String>;
+    /// Autonomous system number
+    fn asn(&self, ip: &IpAddr) -> Option<u32>;
 }
 
 /// IP pattern for matching
@@ -115,6 +128,17 @@ impl IpPattern {
         }
     }
 
+    /// Match, resolving country and ASN patterns through `geo`
+    pub fn matches_with(&self, ip: &IpAddr, geo: Option<&dyn GeoLookup>) -> bool {
+        match (self, geo) {
+            (Self::Country(code), Some(geo)) => geo
+                .country_code(ip)
+                .is_some_and(|country| country.eq_ignore_ascii_case(code)),
+            (Self::Asn(asn), Some(geo)) => geo.asn(ip) == Some(*asn),
+            _ => self.matches(ip),
+        }
+    }
+
     fn matches_cidr(&self, ip: &IpAddr, network: &IpAddr, prefix_len: u8) -> bool {
         match (ip, network) {
             (IpAddr::V4(ip), IpAddr::V4(net)) => {
@@ -257,6 +281,8 @@ pub struct IpFilter<S: IpFilterStore> {
     /// Cached rules for performance
     rules_cache: RwLock<Option<(Vec<IpRule>, DateTime<Utc>)>>,
     cache_ttl_secs: i64,
+    /// Resolves country and ASN rules
+    geo: Option<Arc<dyn GeoLookup>>,
 }
 
 impl<S: IpFilterStore> IpFilter<S> {
@@ -266,9 +292,16 @@ impl<S: IpFilterStore> IpFilter<S> {
             config,
             rules_cache: RwLock::new(None),
             cache_ttl_secs: 60,
+            geo: None,
         }
     }
 
+    /// Enable country and ASN rules
+    pub fn with_geo(mut self, geo: Arc<dyn GeoLookup>) -> Self {
+        self.geo = Some(geo);
+        self
+    }
+
     /// Check if an IP is allowed
     pub async fn check(&self, ip: &str) -> Result<IpCheckResult> {
         let parsed_ip: IpAddr = ip.parse().map_err(|_| Error::InvalidInput {
@@ -282,10 +315,11 @@ impl<S: IpFilterStore> IpFilter<S> {
     /// Check if an IP address is allowed
     pub async fn check_ip(&self, ip: &IpAddr) -> Result<IpCheckResult> {
         let rules = self.get_rules_cached().await?;
+        let geo = self.geo.as_deref();
 
         // Check block rules first
         for rule in rules.iter().filter(|r| r.rule_type == IpRuleType::Block) {
-            if rule.matches(ip) {
+            if rule.matches_with(ip, geo) {
                 // Update hit count asynchronously
                 let _ = self.store.increment_hit_count(rule.id).await;
                 return Ok(IpCheckResult::blocked(rule.clone()));
@@ -296,7 +330,7 @@ impl<S: IpFilterStore> IpFilter<S> {
         let has_allow_rules = rules.iter().any(|r| r.rule_type == IpRuleType::Allow);
         if has_allow_rules {
             for rule in rules.iter().filter(|r| r.rule_type == IpRuleType::Allow) {
-                if rule.matches(ip) {
+                if rule.matches_with(ip, geo) {
                     let _ = self.store.increment_hit_count(rule.id).await;
                     return Ok(IpCheckResult::allowed());
                 }
@@ -668,4 +702,38 @@ mod tests {
         let result = filter.check("11.1.2.3").await.unwrap();
         assert!(result.allowed);
     }
+
+    struct StaticGeo;
+
+    impl GeoLookup for StaticGeo {
+        fn country_code(&self, ip: &IpAddr) -> Option<String> {
+            (ip.to_string() == "81.2.69.142").then(|| "GB".to_string())
+        }
+
+        fn asn(&self, _ip: &IpAddr) -> Option<u32> {
+            Some(64512)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_country_and_asn_blocking() {
+        let filter = IpFilter::new(InMemoryIpFilterStore::new(), IpFilterConfig::default());
+        filter
+            .block(IpPattern::Country("gb".to_string()), None, None, None)
+            .await
+            .unwrap();
+
+        // Without a geo lookup, country rules never match
+        assert!(filter.check("81.2.69.142").await.unwrap().allowed);
+
+        let filter = filter.with_geo(Arc::new(StaticGeo));
+        assert!(!filter.check("81.2.69.142").await.unwrap().allowed);
+        assert!(filter.check("5.6.7.8").await.unwrap().allowed);
+
+        filter
+            .block(IpPattern::Asn(64512), None, None, None)
+            .await
+            .unwrap();
+        assert!(!filter.check("5.6.7.8").await.unwrap().allowed);
+    }
 }
@@ -28,6 +28,7 @@
 //! - **Password Change** (Point 78): Secure password change workflow
 //! - **Impersonation** (Point 79): Admin user impersonation with audit trail
 //! - **WebAuthn** (Point 80): Passwordless authentication with passkeys
+//! - **SAML SSO**: SP-initiated SAML 2.0 login for enterprise identity providers (Okta, ADFS)
 
 // Core authentication modules
 pub mod jwt;
@@ -40,6 +41,9 @@ pub mod tokens;
 pub mod oauth2_client;
 pub mod oauth2_provider;
 
+// Enterprise single sign-on
+pub mod saml;
+
 // Authorization modules
 pub mod middleware;
 pub mod permission;
@@ -71,14 +75,16 @@ pub use csrf::{CsrfConfig, CsrfProtection, CsrfToken};
 pub use impersonation::{
     ImpersonationConfig, ImpersonationManager, ImpersonationRestrictions, ImpersonationSession,
 };
-pub use ip_filter::{IpFilter, IpFilterConfig, IpPattern, IpRule, IpRuleType};
+pub use ip_filter::{GeoLookup, IpFilter, IpFilterConfig, IpPattern, IpRule, IpRuleType};
 pub use jwt::{Claims, JwtConfig, JwtManager, TokenPair, TokenType};
 pub use middleware::{
     AuthContext, AuthMethod, AuthMiddleware, AuthRequest, AuthRequirement, RouteProtection,
 };
 pub use oauth2_client::{OAuth2Client, OAuth2ClientProvider, OAuth2UserInfo, SocialConnection};
 pub use oauth2_provider::{
-    GrantType, OAuth2Client as OAuth2RegisteredClient, OAuth2Provider, OAuth2ProviderConfig,
+    DeviceAuthorization, DeviceAuthorizationResponse, DevicePoll, GrantType,
+    InMemoryOAuth2ProviderStore, OAuth2Client as OAuth2RegisteredClient, OAuth2Provider,
+    OAuth2ProviderConfig, DEVICE_CODE_GRANT_TYPE,
 };
 pub use password::{PasswordHasher, PasswordRules, PasswordStrength, PasswordValidator};
 pub use permission::{Permission, PermissionChecker, Role};
@@ -86,6 +92,9 @@ pub use rate_limit::{RateLimitConfig, RateLimitResult, RateLimiter};
 pub use refresh_token::{
     RefreshToken, RefreshTokenConfig, RefreshTokenManager, RefreshTokenStore, RevokeReason,
 };
+pub use saml::{
+    SamlAttributeMapping, SamlClient, SamlIdentityProvider, SamlServiceProvider, SamlUserInfo,
+};
 pub use session::{SameSite, Session, SessionConfig, SessionManager, SessionStore};
 pub use tokens::{
     PasswordResetToken, SecureToken, TokenManager, TokenStore, TokenType as SecureTokenType,
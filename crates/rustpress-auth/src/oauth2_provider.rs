@@ -50,6 +50,13 @@ impl OAuth2Client {
     }
 }
 
+/// `grant_type` value for exchanging a device code (RFC 8628)
+pub const DEVICE_CODE_GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:device_code";
+
+/// Characters used in user codes: consonants only, so codes cannot spell
+/// words and survive being read aloud or typed on a phone
+const USER_CODE_ALPHABET: &[u8] = b"BCDFGHJKLMNPQRSTVWXZ";
+
 /// OAuth2 grant types
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -59,6 +66,7 @@ pub enum GrantType {
     ClientCredentials,
     Password,
     Implicit,
+    DeviceCode,
 }
 
 impl std::fmt::Display for GrantType {
@@ -69,6 +77,7 @@ impl std::fmt::Display for GrantType {
             Self::ClientCredentials => write!(f, "client_credentials"),
             Self::Password => write!(f, "password"),
             Self::Implicit => write!(f, "implicit"),
+            Self::DeviceCode => write!(f, "{}", DEVICE_CODE_GRANT_TYPE),
         }
     }
 }
@@ -104,6 +113,66 @@ pub enum CodeChallengeMethod {
     S256,
 }
 
+/// State of a device authorization request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeviceAuthorizationStatus {
+    Pending,
+    Approved,
+    Denied,
+    /// Approved and exchanged for tokens
+    Consumed,
+}
+
+/// Device authorization request (RFC 8628)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceAuthorization {
+    pub id: Uuid,
+    pub device_code_hash: String,
+    /// Short code the user enters on another device, e.g. `BDFH-KLMN`
+    pub user_code: String,
+    pub client_id: String,
+    pub scopes: HashSet<String>,
+    pub status: DeviceAuthorizationStatus,
+    pub user_id: Option<Uuid>,
+    /// Minimum seconds between polls; raised when the client polls too fast
+    pub interval_secs: i64,
+    pub last_polled_at: Option<DateTime<Utc>>,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl DeviceAuthorization {
+    pub fn is_expired(&self) -> bool {
+        Utc::now() >= self.expires_at
+    }
+}
+
+/// Device authorization response (RFC 8628 section 3.2)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceAuthorizationResponse {
+    pub device_code: String,
+    pub user_code: String,
+    pub verification_uri: String,
+    pub verification_uri_complete: Option<String>,
+    pub expires_in: i64,
+    pub interval: i64,
+}
+
+/// Outcome of polling with a device code
+#[derive(Debug, Clone)]
+pub enum DevicePoll<T> {
+    /// The user has not acted yet
+    Pending,
+    /// The client polled before its interval elapsed; the interval grew
+    SlowDown,
+    /// The user refused the request
+    Denied,
+    /// The device code expired before the user approved it
+    Expired,
+    Complete(T),
+}
+
 /// OAuth2 access token
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OAuth2AccessToken {
@@ -165,6 +234,10 @@ pub struct OAuth2ProviderConfig {
     pub refresh_token_lifetime: Duration,
     pub allow_public_clients: bool,
     pub require_pkce: bool,
+    pub device_code_lifetime: Duration,
+    pub device_poll_interval: Duration,
+    /// Where users enter device user codes
+    pub verification_uri: String,
 }
 
 impl Default for OAuth2ProviderConfig {
@@ -176,6 +249,9 @@ impl Default for OAuth2ProviderConfig {
             refresh_token_lifetime: Duration::days(30),
             allow_public_clients: true,
             require_pkce: true,
+            device_code_lifetime: Duration::minutes(10),
+            device_poll_interval: Duration::seconds(5),
+            verification_uri: "/admin/device".to_string(),
         }
     }
 }
@@ -205,6 +281,29 @@ pub trait OAuth2ProviderStore: Send + Sync {
     async fn get_refresh_token(&self, token_hash: &str) -> Result<Option<OAuth2RefreshToken>>;
     async fn revoke_refresh_token(&self, id: Uuid) -> Result<()>;
 
+    // Device authorizations
+    async fn store_device_authorization(&self, auth: &DeviceAuthorization) -> Result<()>;
+    async fn get_device_authorization(
+        &self,
+        device_code_hash: &str,
+    ) -> Result<Option<DeviceAuthorization>>;
+    async fn get_device_authorization_by_user_code(
+        &self,
+        user_code: &str,
+    ) -> Result<Option<DeviceAuthorization>>;
+    async fn update_device_authorization(&self, auth: &DeviceAuthorization) -> Result<()>;
+    /// Record a poll without touching the status, so a concurrent approval
+    /// is never overwritten
+    async fn record_device_poll(
+        &self,
+        id: Uuid,
+        polled_at: DateTime<Utc>,
+        interval_secs: i64,
+    ) -> Result<()>;
+    /// Move an approved authorization to consumed; `false` when it was not
+    /// approved anymore, e.g. another poll consumed it first
+    async fn consume_device_authorization(&self, id: Uuid) -> Result<bool>;
+
     // Cleanup
     async fn cleanup_expired(&self) -> Result<u64>;
 }
@@ -612,6 +711,217 @@ impl<S: OAuth2ProviderStore> OAuth2Provider<S> {
         self.generate_tokens(&client, None, &scopes).await
     }
 
+    /// Generate a user code such as `BDFH-KLMN`
+    fn generate_user_code() -> String {
+        let mut rng = rand::thread_rng();
+        let chars: String = (0..8)
+            .map(|_| USER_CODE_ALPHABET[rng.gen_range(0..USER_CODE_ALPHABET.len())] as char)
+            .collect();
+        format!("{}-{}", &chars[..4], &chars[4..])
+    }
+
+    /// Normalize a user code as typed: case, spaces and dashes are ignored
+    pub fn normalize_user_code(input: &str) -> Option<String> {
+        let chars: String = input
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != '-')
+            .map(|c| c.to_ascii_uppercase())
+            .collect();
+        if chars.len() != 8 || !chars.bytes().all(|b| USER_CODE_ALPHABET.contains(&b)) {
+            return None;
+        }
+        Some(format!("{}-{}", &chars[..4], &chars[4..]))
+    }
+
+    /// Start a device authorization grant (RFC 8628)
+    pub async fn create_device_authorization(
+        &self,
+        client_id: &str,
+        client_secret: Option<&str>,
+        scopes: HashSet<String>,
+    ) -> Result<DeviceAuthorizationResponse> {
+        let client = self.authenticate_client(client_id, client_secret).await?;
+
+        if !client.supports_grant_type(&GrantType::DeviceCode) {
+            return Err(Error::InvalidInput {
+                field: "grant_type".to_string(),
+                message: "Grant type not supported".to_string(),
+            });
+        }
+
+        for scope in &scopes {
+            if !client.has_scope(scope) {
+                return Err(Error::InvalidInput {
+                    field: "scope".to_string(),
+                    message: format!("Invalid scope: {}", scope),
+                });
+            }
+        }
+
+        let device_code = Self::generate_token(32);
+        let user_code = Self::generate_user_code();
+        let now = Utc::now();
+
+        let auth = DeviceAuthorization {
+            id: Uuid::now_v7(),
+            device_code_hash: Self::hash_token(&device_code),
+            user_code: user_code.clone(),
+            client_id: client_id.to_string(),
+            scopes,
+            status: DeviceAuthorizationStatus::Pending,
+            user_id: None,
+            interval_secs: self.config.device_poll_interval.num_seconds(),
+            last_polled_at: None,
+            expires_at: now + self.config.device_code_lifetime,
+            created_at: now,
+        };
+        self.store.store_device_authorization(&auth).await?;
+
+        let separator = if self.config.verification_uri.contains('?') {
+            '&'
+        } else {
+            '?'
+        };
+        Ok(DeviceAuthorizationResponse {
+            device_code,
+            verification_uri_complete: Some(format!(
+                "{}{}user_code={}",
+                self.config.verification_uri, separator, user_code
+            )),
+            user_code,
+            verification_uri: self.config.verification_uri.clone(),
+            expires_in: self.config.device_code_lifetime.num_seconds(),
+            interval: auth.interval_secs,
+        })
+    }
+
+    /// Look up a pending device authorization by the code the user entered
+    pub async fn find_device_authorization(&self, user_code: &str) -> Result<DeviceAuthorization> {
+        let invalid = || Error::InvalidInput {
+            field: "user_code".to_string(),
+            message: "Unknown or expired code".to_string(),
+        };
+        let user_code = Self::normalize_user_code(user_code).ok_or_else(invalid)?;
+
+        let auth = self
+            .store
+            .get_device_authorization_by_user_code(&user_code)
+            .await?
+            .ok_or_else(invalid)?;
+
+        if auth.status != DeviceAuthorizationStatus::Pending || auth.is_expired() {
+            return Err(invalid());
+        }
+        Ok(auth)
+    }
+
+    /// Record the user's decision on a device authorization
+    pub async fn resolve_device_authorization(
+        &self,
+        user_code: &str,
+        user_id: Uuid,
+        approve: bool,
+    ) -> Result<DeviceAuthorization> {
+        let mut auth = self.find_device_authorization(user_code).await?;
+        auth.user_id = Some(user_id);
+        auth.status = if approve {
+            DeviceAuthorizationStatus::Approved
+        } else {
+            DeviceAuthorizationStatus::Denied
+        };
+        self.store.update_device_authorization(&auth).await?;
+        Ok(auth)
+    }
+
+    /// Poll with a device code. An approved authorization is returned once
+    /// and then consumed.
+    pub async fn poll_device_code(
+        &self,
+        device_code: &str,
+        client_id: &str,
+        client_secret: Option<&str>,
+    ) -> Result<DevicePoll<DeviceAuthorization>> {
+        self.authenticate_client(client_id, client_secret).await?;
+
+        let device_code_hash = Self::hash_token(device_code);
+        let mut auth = self
+            .store
+            .get_device_authorization(&device_code_hash)
+            .await?
+            .ok_or_else(|| Error::Authentication {
+                message: "Invalid device code".to_string(),
+            })?;
+
+        if auth.client_id != client_id {
+            return Err(Error::Authentication {
+                message: "Client mismatch".to_string(),
+            });
+        }
+
+        if auth.is_expired() {
+            return Ok(DevicePoll::Expired);
+        }
+
+        let now = Utc::now();
+        let too_fast = auth
+            .last_polled_at
+            .is_some_and(|last| now < last + Duration::seconds(auth.interval_secs));
+        if too_fast {
+            auth.interval_secs += 5;
+        }
+        self.store
+            .record_device_poll(auth.id, now, auth.interval_secs)
+            .await?;
+        if too_fast {
+            return Ok(DevicePoll::SlowDown);
+        }
+
+        let used = || Error::Authentication {
+            message: "Device code already used".to_string(),
+        };
+        match auth.status {
+            DeviceAuthorizationStatus::Pending => Ok(DevicePoll::Pending),
+            DeviceAuthorizationStatus::Denied => Ok(DevicePoll::Denied),
+            DeviceAuthorizationStatus::Consumed => Err(used()),
+            DeviceAuthorizationStatus::Approved => {
+                // Concurrent polls may both have read the approval; only the
+                // one that flips it to consumed gets the tokens
+                if !self.store.consume_device_authorization(auth.id).await? {
+                    return Err(used());
+                }
+                auth.status = DeviceAuthorizationStatus::Consumed;
+                auth.last_polled_at = Some(now);
+                Ok(DevicePoll::Complete(auth))
+            }
+        }
+    }
+
+    /// Exchange an approved device code for tokens
+    pub async fn exchange_device_code(
+        &self,
+        device_code: &str,
+        client_id: &str,
+        client_secret: Option<&str>,
+    ) -> Result<DevicePoll<TokenResponse>> {
+        let poll = self
+            .poll_device_code(device_code, client_id, client_secret)
+            .await?;
+
+        Ok(match poll {
+            DevicePoll::Complete(auth) => {
+                let client = self.authenticate_client(client_id, client_secret).await?;
+                DevicePoll::Complete(
+                    self.generate_tokens(&client, auth.user_id, &auth.scopes)
+                        .await?,
+                )
+            }
+            DevicePoll::Pending => DevicePoll::Pending,
+            DevicePoll::SlowDown => DevicePoll::SlowDown,
+            DevicePoll::Denied => DevicePoll::Denied,
+            DevicePoll::Expired => DevicePoll::Expired,
+        })
+    }
+
     /// Get config
     pub fn config(&self) -> &OAuth2ProviderConfig {
         &self.config
@@ -624,6 +934,7 @@ pub struct InMemoryOAuth2ProviderStore {
     auth_codes: RwLock<HashMap<String, AuthorizationCode>>,
     access_tokens: RwLock<HashMap<String, OAuth2AccessToken>>,
     refresh_tokens: RwLock<HashMap<String, OAuth2RefreshToken>>,
+    device_authorizations: RwLock<HashMap<String, DeviceAuthorization>>,
 }
 
 impl InMemoryOAuth2ProviderStore {
@@ -633,7 +944,16 @@ impl InMemoryOAuth2ProviderStore {
             auth_codes: RwLock::new(HashMap::new()),
             access_tokens: RwLock::new(HashMap::new()),
             refresh_tokens: RwLock::new(HashMap::new()),
+            device_authorizations: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Add a client up front, e.g. a first-party application
+    pub fn with_client(self, client: OAuth2Client) -> Self {
+        if let Ok(mut clients) = self.clients.write() {
+            clients.insert(client.client_id.clone(), client);
         }
+        self
     }
 }
 
@@ -803,6 +1123,87 @@ impl OAuth2ProviderStore for InMemoryOAuth2ProviderStore {
         Ok(())
     }
 
+    async fn store_device_authorization(&self, auth: &DeviceAuthorization) -> Result<()> {
+        let mut auths = self
+            .device_authorizations
+            .write()
+            .map_err(|_| Error::Internal {
+                message: "Lock poisoned".to_string(),
+                request_id: None,
+            })?;
+        auths.insert(auth.device_code_hash.clone(), auth.clone());
+        Ok(())
+    }
+
+    async fn get_device_authorization(
+        &self,
+        device_code_hash: &str,
+    ) -> Result<Option<DeviceAuthorization>> {
+        let auths = self
+            .device_authorizations
+            .read()
+            .map_err(|_| Error::Internal {
+                message: "Lock poisoned".to_string(),
+                request_id: None,
+            })?;
+        Ok(auths.get(device_code_hash).cloned())
+    }
+
+    async fn get_device_authorization_by_user_code(
+        &self,
+        user_code: &str,
+    ) -> Result<Option<DeviceAuthorization>> {
+        let auths = self
+            .device_authorizations
+            .read()
+            .map_err(|_| Error::Internal {
+                message: "Lock poisoned".to_string(),
+                request_id: None,
+            })?;
+        Ok(auths.values().find(|a| a.user_code == user_code).cloned())
+    }
+
+    async fn update_device_authorization(&self, auth: &DeviceAuthorization) -> Result<()> {
+        self.store_device_authorization(auth).await
+    }
+
+    async fn record_device_poll(
+        &self,
+        id: Uuid,
+        polled_at: DateTime<Utc>,
+        interval_secs: i64,
+    ) -> Result<()> {
+        let mut auths = self
+            .device_authorizations
+            .write()
+            .map_err(|_| Error::Internal {
+                message: "Lock poisoned".to_string(),
+                request_id: None,
+            })?;
+        if let Some(auth) = auths.values_mut().find(|a| a.id == id) {
+            auth.last_polled_at = Some(polled_at);
+            auth.interval_secs = interval_secs;
+        }
+        Ok(())
+    }
+
+    async fn consume_device_authorization(&self, id: Uuid) -> Result<bool> {
+        let mut auths = self
+            .device_authorizations
+            .write()
+            .map_err(|_| Error::Internal {
+                message: "Lock poisoned".to_string(),
+                request_id: None,
+            })?;
+        match auths.values_mut().find(|a| a.id == id) {
+            Some(auth) if auth.status == DeviceAuthorizationStatus::Approved => {
+                auth.status = DeviceAuthorizationStatus::Consumed;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
     async fn cleanup_expired(&self) -> Result<u64> {
         let now = Utc::now();
         let mut count = 0;
@@ -837,6 +1238,19 @@ impl OAuth2ProviderStore for InMemoryOAuth2ProviderStore {
             count += (before - tokens.len()) as u64;
         }
 
+        {
+            let mut auths = self
+                .device_authorizations
+                .write()
+                .map_err(|_| Error::Internal {
+                    message: "Lock poisoned".to_string(),
+                    request_id: None,
+                })?;
+            let before = auths.len();
+            auths.retain(|_, a| a.expires_at > now);
+            count += (before - auths.len()) as u64;
+        }
+
         Ok(count)
     }
 }
@@ -902,4 +1316,142 @@ mod tests {
         assert!(!response.access_token.is_empty());
         assert_eq!(response.token_type, "Bearer");
     }
+
+    #[tokio::test]
+    async fn test_device_code_flow() {
+        let store = InMemoryOAuth2ProviderStore::new();
+        let config = OAuth2ProviderConfig {
+            device_poll_interval: Duration::zero(),
+            ..Default::default()
+        };
+        let provider = OAuth2Provider::new(store, config);
+
+        let (client, _) = provider
+            .register_client(
+                "CLI".to_string(),
+                vec![],
+                ["read".to_string()].into_iter().collect(),
+                [GrantType::DeviceCode].into_iter().collect(),
+                false,
+                None,
+            )
+            .await
+            .unwrap();
+
+        let device = provider
+            .create_device_authorization(
+                &client.client_id,
+                None,
+                ["read".to_string()].into_iter().collect(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(device.user_code.len(), 9);
+        assert!(device
+            .verification_uri_complete
+            .unwrap()
+            .ends_with(&device.user_code));
+
+        let poll = provider
+            .exchange_device_code(&device.device_code, &client.client_id, None)
+            .await
+            .unwrap();
+        assert!(matches!(poll, DevicePoll::Pending));
+
+        // Codes are accepted regardless of case and separators
+        let typed = device.user_code.replace('-', " ").to_lowercase();
+        provider
+            .resolve_device_authorization(&typed, Uuid::now_v7(), true)
+            .await
+            .unwrap();
+
+        let poll = provider
+            .exchange_device_code(&device.device_code, &client.client_id, None)
+            .await
+            .unwrap();
+        let DevicePoll::Complete(tokens) = poll else {
+            panic!("expected tokens");
+        };
+        assert_eq!(tokens.token_type, "Bearer");
+
+        // The device code cannot be exchanged twice
+        assert!(provider
+            .exchange_device_code(&device.device_code, &client.client_id, None)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_approved_device_code_is_consumed_once() {
+        let store = InMemoryOAuth2ProviderStore::new();
+        let provider = OAuth2Provider::new(store, OAuth2ProviderConfig::default());
+        let (client, _) = provider
+            .register_client(
+                "CLI".to_string(),
+                vec![],
+                HashSet::new(),
+                [GrantType::DeviceCode].into_iter().collect(),
+                false,
+                None,
+            )
+            .await
+            .unwrap();
+        let device = provider
+            .create_device_authorization(&client.client_id, None, HashSet::new())
+            .await
+            .unwrap();
+        let auth = provider
+            .resolve_device_authorization(&device.user_code, Uuid::now_v7(), true)
+            .await
+            .unwrap();
+
+        // A poll that read the approval before another consumed it loses
+        assert!(provider
+            .store
+            .consume_device_authorization(auth.id)
+            .await
+            .unwrap());
+        assert!(!provider
+            .store
+            .consume_device_authorization(auth.id)
+            .await
+            .unwrap());
+        assert!(provider
+            .poll_device_code(&device.device_code, &client.client_id, None)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_device_code_slow_down() {
+        let store = InMemoryOAuth2ProviderStore::new();
+        let provider = OAuth2Provider::new(store, OAuth2ProviderConfig::default());
+
+        let (client, _) = provider
+            .register_client(
+                "CLI".to_string(),
+                vec![],
+                HashSet::new(),
+                [GrantType::DeviceCode].into_iter().collect(),
+                false,
+                None,
+            )
+            .await
+            .unwrap();
+        let device = provider
+            .create_device_authorization(&client.client_id, None, HashSet::new())
+            .await
+            .unwrap();
+
+        let first = provider
+            .poll_device_code(&device.device_code, &client.client_id, None)
+            .await
+            .unwrap();
+        assert!(matches!(first, DevicePoll::Pending));
+        let second = provider
+            .poll_device_code(&device.device_code, &client.client_id, None)
+            .await
+            .unwrap();
+        assert!(matches!(second, DevicePoll::SlowDown));
+    }
 }
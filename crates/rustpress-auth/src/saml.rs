@@ -0,0 +1,1623 @@
+//! SAML 2.0 Single Sign-On
+//!
+//! SP-initiated SAML login for enterprise identity providers such as Okta and
+//! ADFS, next to the social logins of [`crate::oauth2_client`]:
+//!
+//! - [`SamlClient::metadata_xml`] describes this service provider to the IdP
+//! - [`SamlClient::get_authn_request_url`] starts a login with an
+//!   `AuthnRequest` over the HTTP-Redirect binding
+//! - [`SamlClient::handle_response`] validates the `SAMLResponse` posted back
+//!   to the assertion consumer service and maps its attributes to a RustPress
+//!   role
+//!
+//! Responses must answer a pending request; unsolicited IdP-initiated
+//! responses are rejected. The assertion, or the response carrying it, must be
+//! signed with one of the IdP's configured certificates. Signatures are checked
+//! with Exclusive XML Canonicalization over the element their reference points
+//! at, and only that element is read afterwards, so wrapped unsigned content is
+//! never used. Encrypted assertions are not supported.
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine as _;
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use quick_xml::escape::{escape, unescape};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use ring::signature;
+use rustpress_core::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha512};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::io::Write;
+use uuid::Uuid;
+
+const PROTOCOL_NS: &str = "urn:oasis:names:tc:SAML:2.0:protocol";
+const ASSERTION_NS: &str = "urn:oasis:names:tc:SAML:2.0:assertion";
+const METADATA_NS: &str = "urn:oasis:names:tc:SAML:2.0:metadata";
+const DSIG_NS: &str = "http://www.w3.org/2000/09/xmldsig#";
+const XML_NS: &str = "http://www.w3.org/XML/1998/namespace";
+
+const HTTP_POST_BINDING: &str = "urn:oasis:names:tc:SAML:2.0:bindings:HTTP-POST";
+const STATUS_SUCCESS: &str = "urn:oasis:names:tc:SAML:2.0:status:Success";
+const BEARER_METHOD: &str = "urn:oasis:names:tc:SAML:2.0:cm:bearer";
+
+const EXC_C14N: &str = "http://www.w3.org/2001/10/xml-exc-c14n#";
+const ENVELOPED_SIGNATURE: &str = "http://www.w3.org/2000/09/xmldsig#enveloped-signature";
+
+/// E-mail address name identifiers
+pub const NAMEID_FORMAT_EMAIL: &str = "urn:oasis:names:tc:SAML:1.1:nameid-format:emailAddress";
+
+/// Opaque, stable per-user name identifiers
+pub const NAMEID_FORMAT_PERSISTENT: &str = "urn:oasis:names:tc:SAML:2.0:nameid-format:persistent";
+
+/// Largest accepted `SAMLResponse`, decoded
+const MAX_RESPONSE_BYTES: usize = 512 * 1024;
+
+/// This site as a SAML service provider
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SamlServiceProvider {
+    /// Entity id the IdP knows this site by, usually the metadata URL
+    pub entity_id: String,
+    /// Assertion consumer service URL responses are posted to
+    pub acs_url: String,
+    /// Name identifier format requested from the IdP
+    pub name_id_format: String,
+}
+
+impl SamlServiceProvider {
+    pub fn new(entity_id: String, acs_url: String) -> Self {
+        Self {
+            entity_id,
+            acs_url,
+            name_id_format: NAMEID_FORMAT_EMAIL.to_string(),
+        }
+    }
+}
+
+/// SAML identity provider configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SamlIdentityProvider {
+    pub name: String,
+    pub entity_id: String,
+    /// Single sign-on URL for the HTTP-Redirect binding
+    pub sso_url: String,
+    /// Certificates (PEM or base64 DER) or public keys (PEM) the IdP signs
+    /// with; several allow certificate rollover
+    pub certificates: Vec<String>,
+    pub enabled: bool,
+    /// Require the assertion itself to be signed, not only the response
+    pub want_assertions_signed: bool,
+    /// Accept SHA-1 digests and signatures, for older ADFS farms
+    pub allow_sha1: bool,
+    pub attribute_mapping: SamlAttributeMapping,
+}
+
+impl SamlIdentityProvider {
+    /// Create a provider with the default attribute mapping
+    pub fn new(name: String, entity_id: String, sso_url: String, certificate: String) -> Self {
+        Self {
+            name,
+            entity_id,
+            sso_url,
+            certificates: vec![certificate],
+            enabled: true,
+            want_assertions_signed: true,
+            allow_sha1: false,
+            attribute_mapping: SamlAttributeMapping::default(),
+        }
+    }
+
+    /// Create an Okta provider from the values of its "View SAML setup
+    /// instructions" page
+    pub fn okta(entity_id: String, sso_url: String, certificate: String) -> Self {
+        Self {
+            attribute_mapping: SamlAttributeMapping::okta(),
+            ..Self::new("okta".to_string(), entity_id, sso_url, certificate)
+        }
+    }
+
+    /// Create an ADFS provider for the federation service at `host`
+    pub fn adfs(host: &str, certificate: String) -> Self {
+        Self {
+            attribute_mapping: SamlAttributeMapping::adfs(),
+            ..Self::new(
+                "adfs".to_string(),
+                format!("http://{}/adfs/services/trust", host),
+                format!("https://{}/adfs/ls/", host),
+                certificate,
+            )
+        }
+    }
+}
+
+/// Maps a group the IdP reports to a RustPress role
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SamlRoleRule {
+    pub group: String,
+    pub role: String,
+}
+
+/// Assertion attributes the user's profile and role are read from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SamlAttributeMapping {
+    /// Falls back to the name identifier when it is an e-mail address
+    pub email: Option<String>,
+    pub display_name: Option<String>,
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    pub groups: Option<String>,
+    /// Checked in order; the first rule matching one of the user's groups
+    /// decides the role
+    pub role_rules: Vec<SamlRoleRule>,
+    /// Role of users no rule matches; `None` refuses them a role
+    pub default_role: Option<String>,
+}
+
+impl Default for SamlAttributeMapping {
+    fn default() -> Self {
+        Self {
+            email: Some("email".to_string()),
+            display_name: Some("displayName".to_string()),
+            first_name: Some("firstName".to_string()),
+            last_name: Some("lastName".to_string()),
+            groups: Some("groups".to_string()),
+            role_rules: Vec::new(),
+            default_role: Some("subscriber".to_string()),
+        }
+    }
+}
+
+impl SamlAttributeMapping {
+    pub fn okta() -> Self {
+        Self::default()
+    }
+
+    pub fn adfs() -> Self {
+        Self {
+            email: Some(
+                "http://schemas.xmlsoap.org/ws/2005/05/identity/claims/emailaddress".to_string(),
+            ),
+            display_name: Some(
+                "http://schemas.xmlsoap.org/ws/2005/05/identity/claims/name".to_string(),
+            ),
+            first_name: Some(
+                "http://schemas.xmlsoap.org/ws/2005/05/identity/claims/givenname".to_string(),
+            ),
+            last_name: Some(
+                "http://schemas.xmlsoap.org/ws/2005/05/identity/claims/surname".to_string(),
+            ),
+            groups: Some(
+                "http://schemas.microsoft.com/ws/2008/06/identity/claims/role".to_string(),
+            ),
+            ..Self::default()
+        }
+    }
+
+    /// The role for a user in `groups`
+    pub fn resolve_role(&self, groups: &[String]) -> Option<String> {
+        self.role_rules
+            .iter()
+            .find(|rule| groups.contains(&rule.group))
+            .map(|rule| rule.role.clone())
+            .or_else(|| self.default_role.clone())
+    }
+}
+
+/// A pending `AuthnRequest`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SamlRequestState {
+    /// Request id the response's `InResponseTo` must carry
+    pub id: String,
+    pub provider: String,
+    pub redirect_after: Option<String>,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl SamlRequestState {
+    pub fn is_valid(&self) -> bool {
+        Utc::now() < self.expires_at
+    }
+}
+
+/// User asserted by an identity provider
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SamlUserInfo {
+    pub provider: String,
+    pub name_id: String,
+    pub name_id_format: Option<String>,
+    /// Lets single logout address this IdP session
+    pub session_index: Option<String>,
+    pub email: Option<String>,
+    pub name: Option<String>,
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    pub groups: Vec<String>,
+    /// RustPress role from the attribute mapping
+    pub role: Option<String>,
+    pub attributes: HashMap<String, Vec<String>>,
+    pub redirect_after: Option<String>,
+}
+
+/// SAML client configuration
+#[derive(Debug, Clone)]
+pub struct SamlConfig {
+    pub request_lifetime: Duration,
+    /// Allowed difference between our clock and the IdP's
+    pub clock_skew: Duration,
+}
+
+impl Default for SamlConfig {
+    fn default() -> Self {
+        Self {
+            request_lifetime: Duration::minutes(10),
+            clock_skew: Duration::minutes(3),
+        }
+    }
+}
+
+/// Pending requests and seen assertions
+#[async_trait::async_trait]
+pub trait SamlRequestStore: Send + Sync {
+    async fn store_request(&self, request: &SamlRequestState) -> Result<()>;
+    async fn get_request(&self, id: &str) -> Result<Option<SamlRequestState>>;
+    async fn delete_request(&self, id: &str) -> Result<()>;
+    /// Remember an assertion id until `expires_at`; `false` when it was
+    /// already seen
+    async fn record_assertion(&self, id: &str, expires_at: DateTime<Utc>) -> Result<bool>;
+    async fn cleanup_expired(&self) -> Result<u64>;
+}
+
+/// SAML service provider for enterprise single sign-on
+pub struct SamlClient<S: SamlRequestStore> {
+    service_provider: SamlServiceProvider,
+    providers: HashMap<String, SamlIdentityProvider>,
+    store: S,
+    config: SamlConfig,
+}
+
+impl<S: SamlRequestStore> SamlClient<S> {
+    pub fn new(service_provider: SamlServiceProvider, store: S, config: SamlConfig) -> Self {
+        Self {
+            service_provider,
+            providers: HashMap::new(),
+            store,
+            config,
+        }
+    }
+
+    /// Register an identity provider; its certificates are checked up front
+    pub fn register_provider(&mut self, provider: SamlIdentityProvider) -> Result<()> {
+        if provider.certificates.is_empty() {
+            return Err(Error::invalid_input(
+                "certificates",
+                "An identity provider needs at least one signing certificate",
+            ));
+        }
+        for certificate in &provider.certificates {
+            SigningCertificate::parse(certificate)?;
+        }
+        self.providers.insert(provider.name.clone(), provider);
+        Ok(())
+    }
+
+    /// Get available providers
+    pub fn get_providers(&self) -> Vec<&SamlIdentityProvider> {
+        self.providers.values().filter(|p| p.enabled).collect()
+    }
+
+    /// Service provider metadata for the IdP
+    pub fn metadata_xml(&self) -> String {
+        let sp = &self.service_provider;
+        format!(
+            concat!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+                r#"<md:EntityDescriptor xmlns:md="{}" entityID="{}">"#,
+                r#"<md:SPSSODescriptor AuthnRequestsSigned="false" WantAssertionsSigned="true" protocolSupportEnumeration="{}">"#,
+                r#"<md:NameIDFormat>{}</md:NameIDFormat>"#,
+                r#"<md:AssertionConsumerService Binding="{}" Location="{}" index="0" isDefault="true"/>"#,
+                r#"</md:SPSSODescriptor>"#,
+                r#"</md:EntityDescriptor>"#
+            ),
+            METADATA_NS,
+            escape(&sp.entity_id),
+            PROTOCOL_NS,
+            escape(&sp.name_id_format),
+            HTTP_POST_BINDING,
+            escape(&sp.acs_url),
+        )
+    }
+
+    /// Get the IdP URL that starts a login, with the `AuthnRequest` sent over
+    /// the HTTP-Redirect binding
+    pub async fn get_authn_request_url(
+        &self,
+        provider_name: &str,
+        redirect_after: Option<String>,
+    ) -> Result<(String, SamlRequestState)> {
+        let provider = self
+            .providers
+            .get(provider_name)
+            .ok_or_else(|| Error::InvalidInput {
+                field: "provider".to_string(),
+                message: format!("Unknown provider: {}", provider_name),
+            })?;
+
+        if !provider.enabled {
+            return Err(Error::InvalidInput {
+                field: "provider".to_string(),
+                message: "Provider is disabled".to_string(),
+            });
+        }
+
+        let now = Utc::now();
+        let request = SamlRequestState {
+            id: format!("_{}", Uuid::now_v7().simple()),
+            provider: provider_name.to_string(),
+            redirect_after,
+            expires_at: now + self.config.request_lifetime,
+            created_at: now,
+        };
+        self.store.store_request(&request).await?;
+
+        let sp = &self.service_provider;
+        let xml = format!(
+            concat!(
+                r#"<samlp:AuthnRequest xmlns:samlp="{}" xmlns:saml="{}" ID="{}" Version="2.0" "#,
+                r#"IssueInstant="{}" Destination="{}" AssertionConsumerServiceURL="{}" ProtocolBinding="{}">"#,
+                r#"<saml:Issuer>{}</saml:Issuer>"#,
+                r#"<samlp:NameIDPolicy Format="{}" AllowCreate="true"/>"#,
+                r#"</samlp:AuthnRequest>"#
+            ),
+            PROTOCOL_NS,
+            ASSERTION_NS,
+            request.id,
+            now.to_rfc3339_opts(SecondsFormat::Secs, true),
+            escape(&provider.sso_url),
+            escape(&sp.acs_url),
+            HTTP_POST_BINDING,
+            escape(&sp.entity_id),
+            escape(&sp.name_id_format),
+        );
+
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder
+            .write_all(xml.as_bytes())
+            .and_then(|_| encoder.finish())
+            .map(|deflated| {
+                let separator = if provider.sso_url.contains('?') {
+                    '&'
+                } else {
+                    '?'
+                };
+                let url = format!(
+                    "{}{}SAMLRequest={}&RelayState={}",
+                    provider.sso_url,
+                    separator,
+                    urlencoding::encode(&STANDARD.encode(deflated)),
+                    urlencoding::encode(&request.id)
+                );
+                (url, request)
+            })
+            .map_err(|e| Error::internal(format!("Failed to encode SAML request: {}", e)))
+    }
+
+    /// Validate a `SAMLResponse` posted to the assertion consumer service
+    pub async fn handle_response(&self, saml_response: &str) -> Result<SamlUserInfo> {
+        let compact: String = saml_response.split_whitespace().collect();
+        let decoded = STANDARD
+            .decode(compact)
+            .map_err(|_| rejected("not valid base64"))?;
+        if decoded.len() > MAX_RESPONSE_BYTES {
+            return Err(rejected("response is too large"));
+        }
+        let xml = String::from_utf8(decoded).map_err(|_| rejected("not valid UTF-8"))?;
+        let response = XmlElement::parse(&xml)?;
+        if !response.is(PROTOCOL_NS, "Response") {
+            return Err(rejected("not a SAML Response"));
+        }
+
+        // Only answers to our own, unexpired requests are accepted, once
+        let request_id = response
+            .attr("InResponseTo")
+            .ok_or_else(|| rejected("unsolicited responses are not accepted"))?;
+        let request = self
+            .store
+            .get_request(request_id)
+            .await?
+            .ok_or_else(|| rejected("unknown or already used request"))?;
+        self.store.delete_request(request_id).await?;
+        if !request.is_valid() {
+            return Err(rejected("login request expired"));
+        }
+        let provider = self
+            .providers
+            .get(&request.provider)
+            .filter(|p| p.enabled)
+            .ok_or_else(|| rejected("identity provider is not available"))?;
+
+        let assertion = self.validate_response(&response, provider)?;
+        let info = self.read_assertion(assertion, provider, request_id)?;
+
+        let expires_at =
+            assertion_expiry(assertion).unwrap_or_else(Utc::now) + self.config.clock_skew;
+        let assertion_id = assertion.attr("ID").unwrap_or_default();
+        if !self
+            .store
+            .record_assertion(assertion_id, expires_at)
+            .await?
+        {
+            return Err(rejected("assertion has already been used"));
+        }
+
+        Ok(SamlUserInfo {
+            redirect_after: request.redirect_after,
+            ..info
+        })
+    }
+
+    /// Check the response envelope and signatures, returning the assertion
+    fn validate_response<'a>(
+        &self,
+        response: &'a XmlElement,
+        provider: &SamlIdentityProvider,
+    ) -> Result<&'a XmlElement> {
+        if response.attr("Version") != Some("2.0") {
+            return Err(rejected("unsupported SAML version"));
+        }
+        if let Some(destination) = response.attr("Destination") {
+            if destination != self.service_provider.acs_url {
+                return Err(rejected("response was sent to another destination"));
+            }
+        }
+        if let Some(issuer) = response.child(ASSERTION_NS, "Issuer") {
+            if issuer.text().trim() != provider.entity_id {
+                return Err(rejected("response issuer does not match the provider"));
+            }
+        }
+
+        let status = response
+            .child(PROTOCOL_NS, "Status")
+            .and_then(|s| s.child(PROTOCOL_NS, "StatusCode"))
+            .and_then(|c| c.attr("Value"))
+            .ok_or_else(|| rejected("response has no status"))?;
+        if status != STATUS_SUCCESS {
+            return Err(Error::unauthorized(format!(
+                "Identity provider refused the login: {}",
+                status
+            )));
+        }
+
+        if response.child(ASSERTION_NS, "EncryptedAssertion").is_some() {
+            return Err(rejected("encrypted assertions are not supported"));
+        }
+        let mut assertions = response.children_named(ASSERTION_NS, "Assertion");
+        let assertion = assertions
+            .next()
+            .ok_or_else(|| rejected("response carries no assertion"))?;
+        if assertions.next().is_some() {
+            return Err(rejected("response carries more than one assertion"));
+        }
+
+        let certificates = provider
+            .certificates
+            .iter()
+            .map(|c| SigningCertificate::parse(c))
+            .collect::<Result<Vec<_>>>()?;
+        let response_signed =
+            verify_enveloped_signature(response, &certificates, provider.allow_sha1)?;
+        let assertion_signed =
+            verify_enveloped_signature(assertion, &certificates, provider.allow_sha1)?;
+        if !assertion_signed && (provider.want_assertions_signed || !response_signed) {
+            return Err(rejected("assertion is not signed"));
+        }
+
+        Ok(assertion)
+    }
+
+    /// Check the assertion's issuer, subject and conditions and read the user
+    fn read_assertion(
+        &self,
+        assertion: &XmlElement,
+        provider: &SamlIdentityProvider,
+        request_id: &str,
+    ) -> Result<SamlUserInfo> {
+        let now = Utc::now();
+        let skew = self.config.clock_skew;
+        let sp = &self.service_provider;
+
+        if assertion.attr("ID").is_none_or(str::is_empty) {
+            return Err(rejected("assertion has no ID"));
+        }
+        let issuer = assertion
+            .child(ASSERTION_NS, "Issuer")
+            .map(|i| i.text())
+            .unwrap_or_default();
+        if issuer.trim() != provider.entity_id {
+            return Err(rejected("assertion issuer does not match the provider"));
+        }
+
+        let subject = assertion
+            .child(ASSERTION_NS, "Subject")
+            .ok_or_else(|| rejected("assertion has no subject"))?;
+        let name_id = subject
+            .child(ASSERTION_NS, "NameID")
+            .ok_or_else(|| rejected("assertion has no NameID"))?;
+        let confirmed = subject
+            .children_named(ASSERTION_NS, "SubjectConfirmation")
+            .filter(|c| c.attr("Method") == Some(BEARER_METHOD))
+            .filter_map(|c| c.child(ASSERTION_NS, "SubjectConfirmationData"))
+            .any(|data| {
+                data.attr("Recipient") == Some(sp.acs_url.as_str())
+                    && data.attr("InResponseTo").is_none_or(|id| id == request_id)
+                    && data
+                        .attr("NotOnOrAfter")
+                        .and_then(parse_instant)
+                        .is_some_and(|t| now < t + skew)
+                    && data
+                        .attr("NotBefore")
+                        .and_then(parse_instant)
+                        .is_none_or(|t| now + skew >= t)
+            });
+        if !confirmed {
+            return Err(rejected("no valid bearer subject confirmation"));
+        }
+
+        let conditions = assertion
+            .child(ASSERTION_NS, "Conditions")
+            .ok_or_else(|| rejected("assertion has no conditions"))?;
+        if let Some(not_before) = conditions.attr("NotBefore") {
+            let not_before =
+                parse_instant(not_before).ok_or_else(|| rejected("invalid NotBefore"))?;
+            if now + skew < not_before {
+                return Err(rejected("assertion is not valid yet"));
+            }
+        }
+        if let Some(not_on_or_after) = conditions.attr("NotOnOrAfter") {
+            let not_on_or_after =
+                parse_instant(not_on_or_after).ok_or_else(|| rejected("invalid NotOnOrAfter"))?;
+            if now >= not_on_or_after + skew {
+                return Err(rejected("assertion has expired"));
+            }
+        }
+        let mut restrictions = conditions
+            .children_named(ASSERTION_NS, "AudienceRestriction")
+            .peekable();
+        if restrictions.peek().is_none() {
+            return Err(rejected("assertion has no audience restriction"));
+        }
+        for restriction in restrictions {
+            let allowed = restriction
+                .children_named(ASSERTION_NS, "Audience")
+                .any(|audience| audience.text().trim() == sp.entity_id);
+            if !allowed {
+                return Err(rejected("assertion is meant for another audience"));
+            }
+        }
+
+        let mut attributes: HashMap<String, Vec<String>> = HashMap::new();
+        for statement in assertion.children_named(ASSERTION_NS, "AttributeStatement") {
+            for attribute in statement.children_named(ASSERTION_NS, "Attribute") {
+                let Some(name) = attribute.attr("Name") else {
+                    continue;
+                };
+                attributes.entry(name.to_string()).or_default().extend(
+                    attribute
+                        .children_named(ASSERTION_NS, "AttributeValue")
+                        .map(|value| value.text().trim().to_string()),
+                );
+            }
+        }
+
+        let mapping = &provider.attribute_mapping;
+        let first = |name: &Option<String>| {
+            name.as_ref()
+                .and_then(|name| attributes.get(name))
+                .and_then(|values| values.iter().find(|v| !v.is_empty()))
+                .cloned()
+        };
+        let name_id_value = name_id.text().trim().to_string();
+        let name_id_format = name_id.attr("Format").map(str::to_string);
+        let email = first(&mapping.email).or_else(|| {
+            (name_id_format.as_deref() == Some(NAMEID_FORMAT_EMAIL)).then(|| name_id_value.clone())
+        });
+        let groups: Vec<String> = mapping
+            .groups
+            .as_ref()
+            .and_then(|name| attributes.get(name))
+            .cloned()
+            .unwrap_or_default();
+
+        Ok(SamlUserInfo {
+            provider: provider.name.clone(),
+            name_id: name_id_value,
+            name_id_format,
+            session_index: assertion
+                .child(ASSERTION_NS, "AuthnStatement")
+                .and_then(|s| s.attr("SessionIndex"))
+                .map(str::to_string),
+            email,
+            name: first(&mapping.display_name),
+            first_name: first(&mapping.first_name),
+            last_name: first(&mapping.last_name),
+            role: mapping.resolve_role(&groups),
+            groups,
+            attributes,
+            redirect_after: None,
+        })
+    }
+}
+
+fn rejected(reason: &str) -> Error {
+    Error::unauthorized(format!("SAML response rejected: {}", reason))
+}
+
+fn parse_instant(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value.trim())
+        .ok()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+/// When the assertion stops being usable, for replay tracking
+fn assertion_expiry(assertion: &XmlElement) -> Option<DateTime<Utc>> {
+    assertion
+        .child(ASSERTION_NS, "Conditions")
+        .and_then(|c| c.attr("NotOnOrAfter"))
+        .and_then(parse_instant)
+}
+
+// =============================================================================
+// XML documents
+// =============================================================================
+
+#[derive(Debug, Clone)]
+enum XmlNode {
+    Element(XmlElement),
+    Text(String),
+}
+
+#[derive(Debug, Clone)]
+struct XmlAttribute {
+    /// Qualified name as written
+    name: String,
+    namespace: String,
+    value: String,
+}
+
+/// An element with its namespaces resolved
+#[derive(Debug, Clone)]
+struct XmlElement {
+    /// Qualified name as written
+    name: String,
+    namespace: String,
+    attributes: Vec<XmlAttribute>,
+    /// Namespaces in scope, by prefix (`""` for the default namespace)
+    scope: BTreeMap<String, String>,
+    children: Vec<XmlNode>,
+}
+
+fn split_qname(name: &str) -> (&str, &str) {
+    name.split_once(':').unwrap_or(("", name))
+}
+
+impl XmlElement {
+    /// Parse a document. Document types and processing instructions are
+    /// refused, so no entities are ever expanded.
+    fn parse(xml: &str) -> Result<XmlElement> {
+        let malformed = |detail: String| rejected(&format!("malformed XML ({})", detail));
+        let xml = xml.replace("\r\n", "\n").replace('\r', "\n");
+        let mut reader = Reader::from_str(&xml);
+        reader.check_end_names(true);
+
+        let mut stack: Vec<XmlElement> = Vec::new();
+        let mut root: Option<XmlElement> = None;
+        let mut ids = HashSet::new();
+        loop {
+            let event = reader.read_event().map_err(|e| malformed(e.to_string()))?;
+            match event {
+                Event::Start(_) | Event::Empty(_) if root.is_some() => {
+                    return Err(malformed("content after the root element".into()));
+                }
+                Event::Start(start) => {
+                    let element = Self::open(&start, stack.last().map(|p| &p.scope), &mut ids)?;
+                    stack.push(element);
+                }
+                Event::Empty(start) => {
+                    let element = Self::open(&start, stack.last().map(|p| &p.scope), &mut ids)?;
+                    match stack.last_mut() {
+                        Some(parent) => parent.children.push(XmlNode::Element(element)),
+                        None => root = Some(element),
+                    }
+                }
+                Event::End(_) => {
+                    let element = stack
+                        .pop()
+                        .ok_or_else(|| malformed("unbalanced tags".into()))?;
+                    match stack.last_mut() {
+                        Some(parent) => parent.children.push(XmlNode::Element(element)),
+                        None => root = Some(element),
+                    }
+                }
+                Event::Text(text) => {
+                    let text = text.unescape().map_err(|e| malformed(e.to_string()))?;
+                    match stack.last_mut() {
+                        Some(parent) => parent.children.push(XmlNode::Text(text.into_owned())),
+                        None if text.trim().is_empty() => {}
+                        None => return Err(malformed("text outside the root element".into())),
+                    }
+                }
+                Event::CData(data) => {
+                    let text = String::from_utf8(data.into_inner().into_owned())
+                        .map_err(|_| malformed("invalid UTF-8".into()))?;
+                    match stack.last_mut() {
+                        Some(parent) => parent.children.push(XmlNode::Text(text)),
+                        None => return Err(malformed("text outside the root element".into())),
+                    }
+                }
+                Event::Comment(_) | Event::Decl(_) => {}
+                Event::PI(_) | Event::DocType(_) => {
+                    return Err(malformed("document types are not accepted".into()))
+                }
+                Event::Eof => break,
+            }
+        }
+
+        match (root, stack.is_empty()) {
+            (Some(root), true) => Ok(root),
+            _ => Err(malformed("incomplete document".into())),
+        }
+    }
+
+    fn open(
+        start: &BytesStart,
+        parent_scope: Option<&BTreeMap<String, String>>,
+        ids: &mut HashSet<String>,
+    ) -> Result<XmlElement> {
+        let malformed = |detail: &str| rejected(&format!("malformed XML ({})", detail));
+        let name = std::str::from_utf8(start.name().as_ref())
+            .map_err(|_| malformed("invalid UTF-8"))?
+            .to_string();
+
+        let mut scope = parent_scope.cloned().unwrap_or_default();
+        let mut raw_attributes = Vec::new();
+        for attribute in start.attributes() {
+            let attribute = attribute.map_err(|e| malformed(&e.to_string()))?;
+            let key = std::str::from_utf8(attribute.key.as_ref())
+                .map_err(|_| malformed("invalid UTF-8"))?
+                .to_string();
+            // Attribute value normalisation, before references are expanded
+            let raw = std::str::from_utf8(&attribute.value)
+                .map_err(|_| malformed("invalid UTF-8"))?
+                .replace(['\t', '\n'], " ");
+            let value = unescape(&raw)
+                .map_err(|e| malformed(&e.to_string()))?
+                .into_owned();
+            if key == "xmlns" {
+                scope.insert(String::new(), value);
+            } else if let Some(prefix) = key.strip_prefix("xmlns:") {
+                scope.insert(prefix.to_string(), value);
+            } else {
+                raw_attributes.push((key, value));
+            }
+        }
+
+        let resolve = |prefix: &str| -> Result<String> {
+            match prefix {
+                "xml" => Ok(XML_NS.to_string()),
+                _ => scope
+                    .get(prefix)
+                    .cloned()
+                    .ok_or_else(|| malformed("undeclared namespace prefix")),
+            }
+        };
+        let (prefix, _) = split_qname(&name);
+        let namespace = if prefix.is_empty() {
+            scope.get("").cloned().unwrap_or_default()
+        } else {
+            resolve(prefix)?
+        };
+
+        let mut attributes = Vec::with_capacity(raw_attributes.len());
+        for (key, value) in raw_attributes {
+            let (prefix, _) = split_qname(&key);
+            let namespace = if prefix.is_empty() {
+                String::new()
+            } else {
+                resolve(prefix)?
+            };
+            // Signature references find elements by ID; duplicates would let a
+            // forged element shadow the signed one
+            if key == "ID" && !ids.insert(value.clone()) {
+                return Err(malformed("duplicate ID"));
+            }
+            attributes.push(XmlAttribute {
+                name: key,
+                namespace,
+                value,
+            });
+        }
+
+        Ok(XmlElement {
+            name,
+            namespace,
+            attributes,
+            scope,
+            children: Vec::new(),
+        })
+    }
+
+    fn local_name(&self) -> &str {
+        split_qname(&self.name).1
+    }
+
+    fn is(&self, namespace: &str, local_name: &str) -> bool {
+        self.namespace == namespace && self.local_name() == local_name
+    }
+
+    /// Unqualified attribute value
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|a| a.name == name)
+            .map(|a| a.value.as_str())
+    }
+
+    fn elements(&self) -> impl Iterator<Item = &XmlElement> {
+        self.children.iter().filter_map(|child| match child {
+            XmlNode::Element(element) => Some(element),
+            XmlNode::Text(_) => None,
+        })
+    }
+
+    fn children_named(
+        &self,
+        namespace: &'static str,
+        local_name: &'static str,
+    ) -> impl Iterator<Item = &XmlElement> {
+        self.elements()
+            .filter(move |element| element.is(namespace, local_name))
+    }
+
+    fn child(&self, namespace: &'static str, local_name: &'static str) -> Option<&XmlElement> {
+        self.children_named(namespace, local_name).next()
+    }
+
+    /// Text directly inside the element
+    fn text(&self) -> String {
+        self.children
+            .iter()
+            .filter_map(|child| match child {
+                XmlNode::Text(text) => Some(text.as_str()),
+                XmlNode::Element(_) => None,
+            })
+            .collect()
+    }
+
+    /// Exclusive XML Canonicalization (without comments) of this element,
+    /// leaving out `skip` and its descendants
+    fn canonicalize(&self, inclusive_prefixes: &[String], skip: Option<&XmlElement>) -> String {
+        let mut out = String::new();
+        self.write_canonical(&BTreeMap::new(), inclusive_prefixes, skip, &mut out);
+        out
+    }
+
+    fn write_canonical(
+        &self,
+        rendered: &BTreeMap<String, String>,
+        inclusive_prefixes: &[String],
+        skip: Option<&XmlElement>,
+        out: &mut String,
+    ) {
+        let mut utilized = BTreeSet::new();
+        utilized.insert(split_qname(&self.name).0);
+        for attribute in &self.attributes {
+            let (prefix, _) = split_qname(&attribute.name);
+            if !prefix.is_empty() && prefix != "xml" {
+                utilized.insert(prefix);
+            }
+        }
+        for prefix in inclusive_prefixes {
+            let prefix = if prefix == "#default" {
+                ""
+            } else {
+                prefix.as_str()
+            };
+            if self.scope.contains_key(prefix) {
+                utilized.insert(prefix);
+            }
+        }
+
+        let mut rendered = rendered.clone();
+        out.push('<');
+        out.push_str(&self.name);
+        for prefix in utilized {
+            let namespace = self.scope.get(prefix).cloned().unwrap_or_default();
+            let needed = if prefix.is_empty() {
+                rendered.get("").map(String::as_str).unwrap_or_default() != namespace
+            } else {
+                rendered.get(prefix) != Some(&namespace)
+            };
+            if needed {
+                if prefix.is_empty() {
+                    out.push_str(" xmlns=\"");
+                } else {
+                    out.push_str(" xmlns:");
+                    out.push_str(prefix);
+                    out.push_str("=\"");
+                }
+                escape_canonical_attr(&namespace, out);
+                out.push('"');
+                rendered.insert(prefix.to_string(), namespace);
+            }
+        }
+
+        let mut attributes: Vec<&XmlAttribute> = self.attributes.iter().collect();
+        attributes.sort_by(|a, b| {
+            (a.namespace.as_str(), split_qname(&a.name).1)
+                .cmp(&(b.namespace.as_str(), split_qname(&b.name).1))
+        });
+        for attribute in attributes {
+            out.push(' ');
+            out.push_str(&attribute.name);
+            out.push_str("=\"");
+            escape_canonical_attr(&attribute.value, out);
+            out.push('"');
+        }
+        out.push('>');
+
+        for child in &self.children {
+            match child {
+                XmlNode::Text(text) => escape_canonical_text(text, out),
+                XmlNode::Element(element) => {
+                    if skip.is_some_and(|skip| std::ptr::eq(skip, element)) {
+                        continue;
+                    }
+                    element.write_canonical(&rendered, inclusive_prefixes, skip, out);
+                }
+            }
+        }
+
+        out.push_str("</");
+        out.push_str(&self.name);
+        out.push('>');
+    }
+}
+
+fn escape_canonical_text(text: &str, out: &mut String) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '\r' => out.push_str("&#xD;"),
+            _ => out.push(c),
+        }
+    }
+}
+
+fn escape_canonical_attr(value: &str, out: &mut String) {
+    for c in value.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '"' => out.push_str("&quot;"),
+            '\t' => out.push_str("&#x9;"),
+            '\n' => out.push_str("&#xA;"),
+            '\r' => out.push_str("&#xD;"),
+            _ => out.push(c),
+        }
+    }
+}
+
+// =============================================================================
+// XML signatures
+// =============================================================================
+
+/// Public key of an IdP signing certificate
+#[derive(Debug, Clone)]
+enum SigningCertificate {
+    /// PKCS#1 `RSAPublicKey`
+    Rsa(Vec<u8>),
+    /// Uncompressed P-256 point
+    EcdsaP256(Vec<u8>),
+}
+
+impl SigningCertificate {
+    /// Read an X.509 certificate (PEM or base64 DER) or a PEM public key
+    fn parse(input: &str) -> Result<Self> {
+        let invalid = |message: &str| Error::invalid_input("certificates", message);
+        let input = input.trim();
+        let (label, body) = match input.strip_prefix("-----BEGIN ") {
+            Some(pem) => {
+                let (label, rest) = pem
+                    .split_once("-----")
+                    .ok_or_else(|| invalid("Malformed PEM"))?;
+                (label, rest.split("-----END ").next().unwrap_or_default())
+            }
+            None => ("CERTIFICATE", input),
+        };
+        let der = STANDARD
+            .decode(body.split_whitespace().collect::<String>())
+            .map_err(|_| invalid("Certificate is not valid base64"))?;
+
+        let spki = match label {
+            "CERTIFICATE" => certificate_spki(&der),
+            "PUBLIC KEY" => der_element(&der, 0x30).map(|(spki, _)| spki),
+            _ => None,
+        }
+        .ok_or_else(|| invalid("Expected an X.509 certificate or public key"))?;
+        let (algorithm, key) = spki_key(spki).ok_or_else(|| invalid("Malformed public key"))?;
+
+        // rsaEncryption (1.2.840.113549.1.1.1) and id-ecPublicKey with
+        // prime256v1 (1.2.840.10045.2.1, 1.2.840.10045.3.1.7)
+        const RSA_ENCRYPTION: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x01];
+        const EC_PUBLIC_KEY: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01];
+        const PRIME256V1: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07];
+        let (oid, parameters) =
+            der_element(algorithm, 0x06).ok_or_else(|| invalid("Malformed public key"))?;
+        if oid == RSA_ENCRYPTION {
+            Ok(Self::Rsa(key.to_vec()))
+        } else if oid == EC_PUBLIC_KEY
+            && der_element(parameters, 0x06).is_some_and(|(curve, _)| curve == PRIME256V1)
+        {
+            Ok(Self::EcdsaP256(key.to_vec()))
+        } else {
+            Err(invalid("Only RSA and P-256 signing keys are supported"))
+        }
+    }
+
+    fn verify(&self, method: SignatureMethod, message: &[u8], signature_value: &[u8]) -> bool {
+        let (algorithm, key): (&dyn signature::VerificationAlgorithm, &[u8]) = match (self, method)
+        {
+            (Self::Rsa(key), SignatureMethod::RsaSha1) => (
+                &signature::RSA_PKCS1_2048_8192_SHA1_FOR_LEGACY_USE_ONLY,
+                key,
+            ),
+            (Self::Rsa(key), SignatureMethod::RsaSha256) => {
+                (&signature::RSA_PKCS1_2048_8192_SHA256, key)
+            }
+            (Self::Rsa(key), SignatureMethod::RsaSha512) => {
+                (&signature::RSA_PKCS1_2048_8192_SHA512, key)
+            }
+            (Self::EcdsaP256(key), SignatureMethod::EcdsaSha256) => {
+                (&signature::ECDSA_P256_SHA256_FIXED, key)
+            }
+            _ => return false,
+        };
+        signature::UnparsedPublicKey::new(algorithm, key)
+            .verify(message, signature_value)
+            .is_ok()
+    }
+}
+
+/// Contents of the DER element with `tag` at the start of `input`, and the
+/// bytes after it
+fn der_element(input: &[u8], tag: u8) -> Option<(&[u8], &[u8])> {
+    let (&found, rest) = input.split_first()?;
+    if found != tag {
+        return None;
+    }
+    let (&len, rest) = rest.split_first()?;
+    let (len, rest) = if len < 0x80 {
+        (len as usize, rest)
+    } else {
+        let octets = (len & 0x7f) as usize;
+        if octets == 0 || octets > 4 || rest.len() < octets {
+            return None;
+        }
+        let (len, rest) = rest.split_at(octets);
+        (len.iter().fold(0usize, |n, &b| (n << 8) | b as usize), rest)
+    };
+    (rest.len() >= len).then(|| rest.split_at(len))
+}
+
+/// Skip the DER element at the start of `input`, whatever its tag
+fn der_skip(input: &[u8]) -> Option<&[u8]> {
+    let tag = *input.first()?;
+    der_element(input, tag).map(|(_, rest)| rest)
+}
+
+/// The SubjectPublicKeyInfo of an X.509 certificate
+fn certificate_spki(der: &[u8]) -> Option<&[u8]> {
+    let (certificate, _) = der_element(der, 0x30)?;
+    let (tbs, _) = der_element(certificate, 0x30)?;
+    // [0] version is optional
+    let mut rest = if tbs.first() == Some(&0xa0) {
+        der_skip(tbs)?
+    } else {
+        tbs
+    };
+    // serialNumber, signature, issuer, validity, subject
+    for _ in 0..5 {
+        rest = der_skip(rest)?;
+    }
+    let (spki, _) = der_element(rest, 0x30)?;
+    Some(spki)
+}
+
+/// Algorithm identifier contents and key bits of a SubjectPublicKeyInfo
+fn spki_key(spki: &[u8]) -> Option<(&[u8], &[u8])> {
+    let (algorithm, rest) = der_element(spki, 0x30)?;
+    let (bits, _) = der_element(rest, 0x03)?;
+    let (&unused_bits, key) = bits.split_first()?;
+    (unused_bits == 0).then_some((algorithm, key))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SignatureMethod {
+    RsaSha1,
+    RsaSha256,
+    RsaSha512,
+    EcdsaSha256,
+}
+
+impl SignatureMethod {
+    fn parse(uri: &str, allow_sha1: bool) -> Option<Self> {
+        match uri {
+            "http://www.w3.org/2000/09/xmldsig#rsa-sha1" if allow_sha1 => Some(Self::RsaSha1),
+            "http://www.w3.org/2001/04/xmldsig-more#rsa-sha256" => Some(Self::RsaSha256),
+            "http://www.w3.org/2001/04/xmldsig-more#rsa-sha512" => Some(Self::RsaSha512),
+            "http://www.w3.org/2001/04/xmldsig-more#ecdsa-sha256" => Some(Self::EcdsaSha256),
+            _ => None,
+        }
+    }
+}
+
+fn digest(uri: &str, allow_sha1: bool, data: &[u8]) -> Option<Vec<u8>> {
+    match uri {
+        "http://www.w3.org/2000/09/xmldsig#sha1" if allow_sha1 => Some(Sha1::digest(data).to_vec()),
+        "http://www.w3.org/2001/04/xmlenc#sha256" => Some(Sha256::digest(data).to_vec()),
+        "http://www.w3.org/2001/04/xmlenc#sha512" => Some(Sha512::digest(data).to_vec()),
+        _ => None,
+    }
+}
+
+/// `InclusiveNamespaces` prefix list of a canonicalization method or transform
+fn inclusive_prefixes(method: &XmlElement) -> Vec<String> {
+    method
+        .child(EXC_C14N, "InclusiveNamespaces")
+        .and_then(|n| n.attr("PrefixList"))
+        .map(|list| list.split_whitespace().map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+fn decode_base64_text(element: &XmlElement) -> Option<Vec<u8>> {
+    STANDARD
+        .decode(element.text().split_whitespace().collect::<String>())
+        .ok()
+}
+
+/// Verify the enveloped signature of `element`, if it has one. The signature
+/// must reference `element` itself; `Ok(false)` means it is unsigned.
+fn verify_enveloped_signature(
+    element: &XmlElement,
+    certificates: &[SigningCertificate],
+    allow_sha1: bool,
+) -> Result<bool> {
+    let mut signatures = element.children_named(DSIG_NS, "Signature");
+    let Some(signature) = signatures.next() else {
+        return Ok(false);
+    };
+    if signatures.next().is_some() {
+        return Err(rejected("more than one signature on an element"));
+    }
+
+    let signed_info = signature
+        .child(DSIG_NS, "SignedInfo")
+        .ok_or_else(|| rejected("signature has no SignedInfo"))?;
+    let c14n = signed_info
+        .child(DSIG_NS, "CanonicalizationMethod")
+        .ok_or_else(|| rejected("signature has no canonicalization method"))?;
+    if c14n.attr("Algorithm") != Some(EXC_C14N) {
+        return Err(rejected("unsupported canonicalization method"));
+    }
+    let method = signed_info
+        .child(DSIG_NS, "SignatureMethod")
+        .and_then(|m| m.attr("Algorithm"))
+        .and_then(|uri| SignatureMethod::parse(uri, allow_sha1))
+        .ok_or_else(|| rejected("unsupported signature method"))?;
+
+    let mut references = signed_info.children_named(DSIG_NS, "Reference");
+    let reference = references
+        .next()
+        .ok_or_else(|| rejected("signature has no reference"))?;
+    if references.next().is_some() {
+        return Err(rejected("signature has more than one reference"));
+    }
+    let id = element.attr("ID").filter(|id| !id.is_empty());
+    if id.is_none() || reference.attr("URI").and_then(|uri| uri.strip_prefix('#')) != id {
+        return Err(rejected("signature does not reference the signed element"));
+    }
+
+    let mut reference_prefixes = Vec::new();
+    let mut canonicalized = false;
+    for transform in reference
+        .child(DSIG_NS, "Transforms")
+        .into_iter()
+        .flat_map(|t| t.children_named(DSIG_NS, "Transform"))
+    {
+        match transform.attr("Algorithm") {
+            Some(ENVELOPED_SIGNATURE) => {}
+            Some(EXC_C14N) => {
+                canonicalized = true;
+                reference_prefixes = inclusive_prefixes(transform);
+            }
+            _ => return Err(rejected("unsupported signature transform")),
+        }
+    }
+    if !canonicalized {
+        return Err(rejected("signature reference is not canonicalized"));
+    }
+
+    let digest_uri = reference
+        .child(DSIG_NS, "DigestMethod")
+        .and_then(|m| m.attr("Algorithm"))
+        .unwrap_or_default();
+    let expected = reference
+        .child(DSIG_NS, "DigestValue")
+        .and_then(decode_base64_text)
+        .ok_or_else(|| rejected("signature has no digest"))?;
+    let actual = digest(
+        digest_uri,
+        allow_sha1,
+        element
+            .canonicalize(&reference_prefixes, Some(signature))
+            .as_bytes(),
+    )
+    .ok_or_else(|| rejected("unsupported digest method"))?;
+    if actual != expected {
+        return Err(rejected("signed content has been altered"));
+    }
+
+    let signature_value = signature
+        .child(DSIG_NS, "SignatureValue")
+        .and_then(decode_base64_text)
+        .ok_or_else(|| rejected("signature has no value"))?;
+    let signed = signed_info.canonicalize(&inclusive_prefixes(c14n), None);
+    if !certificates
+        .iter()
+        .any(|certificate| certificate.verify(method, signed.as_bytes(), &signature_value))
+    {
+        return Err(rejected("signature verification failed"));
+    }
+    Ok(true)
+}
+
+/// In-memory request store
+pub struct InMemorySamlRequestStore {
+    requests: std::sync::RwLock<HashMap<String, SamlRequestState>>,
+    assertions: std::sync::RwLock<HashMap<String, DateTime<Utc>>>,
+}
+
+impl InMemorySamlRequestStore {
+    pub fn new() -> Self {
+        Self {
+            requests: std::sync::RwLock::new(HashMap::new()),
+            assertions: std::sync::RwLock::new(HashMap::new()),
+        }
+    }
+}
+
+impl Default for InMemorySamlRequestStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn lock_poisoned() -> Error {
+    Error::Internal {
+        message: "Lock poisoned".to_string(),
+        request_id: None,
+    }
+}
+
+#[async_trait::async_trait]
+impl SamlRequestStore for InMemorySamlRequestStore {
+    async fn store_request(&self, request: &SamlRequestState) -> Result<()> {
+        let mut requests = self.requests.write().map_err(|_| lock_poisoned())?;
+        requests.insert(request.id.clone(), request.clone());
+        Ok(())
+    }
+
+    async fn get_request(&self, id: &str) -> Result<Option<SamlRequestState>> {
+        let requests = self.requests.read().map_err(|_| lock_poisoned())?;
+        Ok(requests.get(id).cloned())
+    }
+
+    async fn delete_request(&self, id: &str) -> Result<()> {
+        let mut requests = self.requests.write().map_err(|_| lock_poisoned())?;
+        requests.remove(id);
+        Ok(())
+    }
+
+    async fn record_assertion(&self, id: &str, expires_at: DateTime<Utc>) -> Result<bool> {
+        let mut assertions = self.assertions.write().map_err(|_| lock_poisoned())?;
+        let now = Utc::now();
+        if assertions.get(id).is_some_and(|expiry| *expiry > now) {
+            return Ok(false);
+        }
+        assertions.insert(id.to_string(), expires_at);
+        Ok(true)
+    }
+
+    async fn cleanup_expired(&self) -> Result<u64> {
+        let now = Utc::now();
+        let mut requests = self.requests.write().map_err(|_| lock_poisoned())?;
+        let before = requests.len();
+        requests.retain(|_, r| r.expires_at > now);
+        let mut removed = before - requests.len();
+
+        let mut assertions = self.assertions.write().map_err(|_| lock_poisoned())?;
+        let before = assertions.len();
+        assertions.retain(|_, expiry| *expiry > now);
+        removed += before - assertions.len();
+        Ok(removed as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::DeflateDecoder;
+    use ring::rand::SystemRandom;
+    use ring::signature::{EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_FIXED_SIGNING};
+    use std::io::Read;
+
+    const SP_ENTITY: &str = "https://blog.example.com/saml/metadata";
+    const ACS_URL: &str = "https://blog.example.com/saml/acs";
+    const IDP_ENTITY: &str = "http://www.okta.com/exk1";
+
+    fn der(tag: u8, content: &[u8]) -> Vec<u8> {
+        let mut out = vec![tag];
+        if content.len() < 0x80 {
+            out.push(content.len() as u8);
+        } else {
+            out.push(0x81);
+            out.push(content.len() as u8);
+        }
+        out.extend_from_slice(content);
+        out
+    }
+
+    /// A P-256 signing key and a PEM public key for it
+    fn idp_key() -> (EcdsaKeyPair, String) {
+        let rng = SystemRandom::new();
+        let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &rng).unwrap();
+        let pair = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, pkcs8.as_ref(), &rng)
+            .unwrap();
+        let algorithm = [
+            der(0x06, &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01]),
+            der(0x06, &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07]),
+        ]
+        .concat();
+        let mut bits = vec![0];
+        bits.extend_from_slice(pair.public_key().as_ref());
+        let spki = der(0x30, &[der(0x30, &algorithm), der(0x03, &bits)].concat());
+        let pem = format!(
+            "-----BEGIN PUBLIC KEY-----\n{}\n-----END PUBLIC KEY-----",
+            STANDARD.encode(spki)
+        );
+        (pair, pem)
+    }
+
+    fn client(certificate: String) -> SamlClient<InMemorySamlRequestStore> {
+        let mut client = SamlClient::new(
+            SamlServiceProvider::new(SP_ENTITY.to_string(), ACS_URL.to_string()),
+            InMemorySamlRequestStore::new(),
+            SamlConfig::default(),
+        );
+        let mut idp = SamlIdentityProvider::okta(
+            IDP_ENTITY.to_string(),
+            "https://example.okta.com/app/sso/saml".to_string(),
+            certificate,
+        );
+        idp.attribute_mapping.role_rules = vec![
+            SamlRoleRule {
+                group: "wp-admins".to_string(),
+                role: "administrator".to_string(),
+            },
+            SamlRoleRule {
+                group: "writers".to_string(),
+                role: "author".to_string(),
+            },
+        ];
+        client.register_provider(idp).unwrap();
+        client
+    }
+
+    /// A response to `request_id` whose assertion is signed with `key`
+    fn signed_response(key: &EcdsaKeyPair, request_id: &str, name_id: &str) -> String {
+        let now = Utc::now();
+        let later = (now + Duration::minutes(5)).to_rfc3339_opts(SecondsFormat::Secs, true);
+        let now = now.to_rfc3339_opts(SecondsFormat::Secs, true);
+        let template = format!(
+            r##"<samlp:Response xmlns:samlp="{PROTOCOL_NS}" ID="_r1" Version="2.0" IssueInstant="{now}" Destination="{ACS_URL}" InResponseTo="{request_id}">
+  <saml:Issuer xmlns:saml="{ASSERTION_NS}">{IDP_ENTITY}</saml:Issuer>
+  <samlp:Status><samlp:StatusCode Value="{STATUS_SUCCESS}"/></samlp:Status>
+  <saml:Assertion xmlns:saml="{ASSERTION_NS}" ID="_a1" Version="2.0" IssueInstant="{now}">
+    <saml:Issuer>{IDP_ENTITY}</saml:Issuer>
+    <ds:Signature xmlns:ds="{DSIG_NS}">
+      <ds:SignedInfo>
+        <ds:CanonicalizationMethod Algorithm="{EXC_C14N}"/>
+        <ds:SignatureMethod Algorithm="http://www.w3.org/2001/04/xmldsig-more#ecdsa-sha256"/>
+        <ds:Reference URI="#_a1">
+          <ds:Transforms>
+            <ds:Transform Algorithm="{ENVELOPED_SIGNATURE}"/>
+            <ds:Transform Algorithm="{EXC_C14N}"/>
+          </ds:Transforms>
+          <ds:DigestMethod Algorithm="http://www.w3.org/2001/04/xmlenc#sha256"/>
+          <ds:DigestValue>DIGEST</ds:DigestValue>
+        </ds:Reference>
+      </ds:SignedInfo>
+      <ds:SignatureValue>SIGNATURE</ds:SignatureValue>
+    </ds:Signature>
+    <saml:Subject>
+      <saml:NameID Format="{NAMEID_FORMAT_EMAIL}">{name_id}</saml:NameID>
+      <saml:SubjectConfirmation Method="{BEARER_METHOD}">
+        <saml:SubjectConfirmationData InResponseTo="{request_id}" NotOnOrAfter="{later}" Recipient="{ACS_URL}"/>
+      </saml:SubjectConfirmation>
+    </saml:Subject>
+    <saml:Conditions NotBefore="{now}" NotOnOrAfter="{later}">
+      <saml:AudienceRestriction><saml:Audience>{SP_ENTITY}</saml:Audience></saml:AudienceRestriction>
+    </saml:Conditions>
+    <saml:AuthnStatement AuthnInstant="{now}" SessionIndex="_s1"/>
+    <saml:AttributeStatement>
+      <saml:Attribute Name="firstName"><saml:AttributeValue>Ada</saml:AttributeValue></saml:Attribute>
+      <saml:Attribute Name="groups">
+        <saml:AttributeValue>everyone</saml:AttributeValue>
+        <saml:AttributeValue>writers</saml:AttributeValue>
+      </saml:Attribute>
+    </saml:AttributeStatement>
+  </saml:Assertion>
+</samlp:Response>"##
+        );
+
+        let assertion = |xml: &str| {
+            XmlElement::parse(xml)
+                .unwrap()
+                .child(ASSERTION_NS, "Assertion")
+                .unwrap()
+                .clone()
+        };
+        let parsed = assertion(&template);
+        let signature = parsed.child(DSIG_NS, "Signature").unwrap();
+        let digest = Sha256::digest(parsed.canonicalize(&[], Some(signature)).as_bytes());
+        let xml = template.replace("DIGEST", &STANDARD.encode(digest));
+
+        let parsed = assertion(&xml);
+        let signed_info = parsed
+            .child(DSIG_NS, "Signature")
+            .and_then(|s| s.child(DSIG_NS, "SignedInfo"))
+            .unwrap();
+        let value = key
+            .sign(
+                &SystemRandom::new(),
+                signed_info.canonicalize(&[], None).as_bytes(),
+            )
+            .unwrap();
+        STANDARD.encode(xml.replace("SIGNATURE", &STANDARD.encode(value)))
+    }
+
+    #[test]
+    fn test_exclusive_canonicalization() {
+        let xml = "<a:root xmlns:a=\"urn:a\" xmlns:b=\"urn:b\" xmlns=\"urn:d\">\r\n  \
+                   <a:child z=\"1\" b:attr=\"x&#9;y\" a=\"&quot;\"><inner>1 &lt; 2</inner><empty/></a:child>\
+                   </a:root>";
+        let root = XmlElement::parse(xml).unwrap();
+        let child = root.elements().next().unwrap();
+        assert_eq!(
+            child.canonicalize(&[], None),
+            "<a:child xmlns:a=\"urn:a\" xmlns:b=\"urn:b\" a=\"&quot;\" z=\"1\" b:attr=\"x&#x9;y\">\
+             <inner xmlns=\"urn:d\">1 &lt; 2</inner><empty xmlns=\"urn:d\"></empty></a:child>"
+        );
+        assert_eq!(
+            child.canonicalize(&["#default".to_string()], None),
+            "<a:child xmlns=\"urn:d\" xmlns:a=\"urn:a\" xmlns:b=\"urn:b\" a=\"&quot;\" z=\"1\" b:attr=\"x&#x9;y\">\
+             <inner>1 &lt; 2</inner><empty></empty></a:child>"
+        );
+
+        assert!(XmlElement::parse("<!DOCTYPE x [<!ENTITY e \"e\">]><x>&e;</x>").is_err());
+        assert!(XmlElement::parse("<x><y ID=\"1\"/><y ID=\"1\"/></x>").is_err());
+        assert!(XmlElement::parse("<p:x/>").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_authn_request_and_metadata() {
+        let (_, certificate) = idp_key();
+        let client = client(certificate);
+
+        let metadata = client.metadata_xml();
+        let parsed = XmlElement::parse(&metadata).unwrap();
+        assert!(parsed.is(METADATA_NS, "EntityDescriptor"));
+        assert_eq!(parsed.attr("entityID"), Some(SP_ENTITY));
+
+        let (url, request) = client
+            .get_authn_request_url("okta", Some("/wp-admin".to_string()))
+            .await
+            .unwrap();
+        assert!(url.starts_with("https://example.okta.com/app/sso/saml?SAMLRequest="));
+        let encoded = url
+            .split("SAMLRequest=")
+            .nth(1)
+            .and_then(|rest| rest.split('&').next())
+            .unwrap();
+        let deflated = STANDARD
+            .decode(urlencoding::decode(encoded).unwrap().as_ref())
+            .unwrap();
+        let mut xml = String::new();
+        DeflateDecoder::new(deflated.as_slice())
+            .read_to_string(&mut xml)
+            .unwrap();
+        let authn = XmlElement::parse(&xml).unwrap();
+        assert!(authn.is(PROTOCOL_NS, "AuthnRequest"));
+        assert_eq!(authn.attr("ID"), Some(request.id.as_str()));
+        assert_eq!(authn.attr("AssertionConsumerServiceURL"), Some(ACS_URL));
+
+        assert!(client.get_authn_request_url("adfs", None).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_signed_response_is_accepted_once() {
+        let (key, certificate) = idp_key();
+        let client = client(certificate);
+        let (_, request) = client
+            .get_authn_request_url("okta", Some("/wp-admin".to_string()))
+            .await
+            .unwrap();
+
+        let response = signed_response(&key, &request.id, "ada@example.com");
+        let user = client.handle_response(&response).await.unwrap();
+        assert_eq!(user.provider, "okta");
+        assert_eq!(user.name_id, "ada@example.com");
+        assert_eq!(user.email.as_deref(), Some("ada@example.com"));
+        assert_eq!(user.first_name.as_deref(), Some("Ada"));
+        assert_eq!(user.groups, vec!["everyone", "writers"]);
+        assert_eq!(user.role.as_deref(), Some("author"));
+        assert_eq!(user.session_index.as_deref(), Some("_s1"));
+        assert_eq!(user.redirect_after.as_deref(), Some("/wp-admin"));
+
+        // The request is consumed by the first response
+        assert!(client.handle_response(&response).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_tampered_and_foreign_responses_are_rejected() {
+        let (key, certificate) = idp_key();
+        let client = client(certificate);
+
+        let (_, request) = client.get_authn_request_url("okta", None).await.unwrap();
+        let response = signed_response(&key, &request.id, "ada@example.com");
+        let xml = String::from_utf8(STANDARD.decode(&response).unwrap()).unwrap();
+        let tampered = STANDARD.encode(xml.replace("ada@example.com", "admin@example.com"));
+        let err = client.handle_response(&tampered).await.unwrap_err();
+        assert!(err.to_string().contains("altered"));
+
+        // Signed by a key the provider does not trust
+        let (other_key, _) = idp_key();
+        let (_, request) = client.get_authn_request_url("okta", None).await.unwrap();
+        let forged = signed_response(&other_key, &request.id, "ada@example.com");
+        let err = client.handle_response(&forged).await.unwrap_err();
+        assert!(err.to_string().contains("verification failed"));
+
+        // Unsigned assertions are refused
+        let (_, request) = client.get_authn_request_url("okta", None).await.unwrap();
+        let xml = String::from_utf8(
+            STANDARD
+                .decode(signed_response(&key, &request.id, "ada@example.com"))
+                .unwrap(),
+        )
+        .unwrap();
+        let start = xml.find("<ds:Signature").unwrap();
+        let end = xml.find("</ds:Signature>").unwrap() + "</ds:Signature>".len();
+        let unsigned = STANDARD.encode(format!("{}{}", &xml[..start], &xml[end..]));
+        let err = client.handle_response(&unsigned).await.unwrap_err();
+        assert!(err.to_string().contains("not signed"));
+
+        // Responses to requests we never made
+        let unsolicited = signed_response(&key, "_unknown", "ada@example.com");
+        assert!(client.handle_response(&unsolicited).await.is_err());
+    }
+}
@@ -4,8 +4,15 @@ use crate::key::CacheKey;
 use crate::CacheStats;
 use async_trait::async_trait;
 use rustpress_core::error::{Error, Result};
+use rustpress_core::fault::{FaultInjector, FaultTarget};
+#[cfg(feature = "memory")]
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 use std::time::Duration;
 
+/// Prefix of the keys indexing which entries carry a tag
+pub const TAG_INDEX_PREFIX: &str = "tag:";
+
 /// Cache backend trait
 #[async_trait]
 pub trait CacheBackend: Send + Sync {
@@ -36,6 +43,22 @@ pub trait CacheBackend: Send + Sync {
     /// Decrement a numeric value
     async fn decrement(&self, key: &CacheKey, delta: i64) -> Result<i64>;
 
+    /// Atomically increment a fixed-window counter. The counter expires
+    /// `window` after it was created; later increments keep that expiry.
+    async fn increment_window(&self, key: &CacheKey, delta: i64, window: Duration) -> Result<i64>;
+
+    /// Set a value and record it under each of `tags`
+    async fn set_with_tags(
+        &self,
+        key: &CacheKey,
+        value: Vec<u8>,
+        ttl: Option<Duration>,
+        tags: &[String],
+    ) -> Result<()>;
+
+    /// Delete every entry set with `tag`; returns how many were deleted
+    async fn invalidate_tag(&self, tag: &str) -> Result<u64>;
+
     /// Get multiple values
     async fn get_many(&self, keys: &[CacheKey]) -> Result<Vec<Option<Vec<u8>>>> {
         let mut results = Vec::with_capacity(keys.len());
@@ -71,6 +94,9 @@ pub trait CacheBackend: Send + Sync {
 #[cfg(feature = "memory")]
 pub struct MemoryBackend {
     cache: moka::future::Cache<String, Vec<u8>>,
+    /// Keys set under each tag. Held across the matching cache writes so
+    /// an invalidation never interleaves with a tagged set.
+    tags: tokio::sync::Mutex<HashMap<String, HashSet<String>>>,
 }
 
 #[cfg(feature = "memory")]
@@ -80,6 +106,7 @@ impl MemoryBackend {
             cache: moka::future::Cache::builder()
                 .max_capacity(max_capacity)
                 .build(),
+            tags: Default::default(),
         }
     }
 
@@ -89,6 +116,7 @@ impl MemoryBackend {
                 .max_capacity(max_capacity)
                 .time_to_live(default_ttl)
                 .build(),
+            tags: Default::default(),
         }
     }
 }
@@ -125,6 +153,7 @@ impl CacheBackend for MemoryBackend {
 
     async fn clear(&self) -> Result<()> {
         self.cache.invalidate_all();
+        self.tags.lock().await.clear();
         Ok(())
     }
 
@@ -151,15 +180,75 @@ impl CacheBackend for MemoryBackend {
         self.increment(key, -delta).await
     }
 
+    async fn increment_window(&self, key: &CacheKey, delta: i64, window: Duration) -> Result<i64> {
+        // Moka has no per-key TTL, so the window end is stored next to the
+        // count as "count:expires_at_ms"; the entry lock serializes updates
+        let now = chrono::Utc::now().timestamp_millis();
+        let entry = self
+            .cache
+            .entry(key.as_str())
+            .and_upsert_with(|existing| {
+                let (count, expires_at) = existing
+                    .and_then(|entry| parse_window(entry.value()))
+                    .filter(|(_, expires_at)| *expires_at > now)
+                    .unwrap_or((0, now + window.as_millis() as i64));
+                std::future::ready(format!("{}:{}", count + delta, expires_at).into_bytes())
+            })
+            .await;
+
+        Ok(parse_window(entry.value())
+            .map(|(count, _)| count)
+            .unwrap_or(delta))
+    }
+
+    async fn set_with_tags(
+        &self,
+        key: &CacheKey,
+        value: Vec<u8>,
+        _ttl: Option<Duration>,
+        tags: &[String],
+    ) -> Result<()> {
+        let mut index = self.tags.lock().await;
+        for tag in tags {
+            index.entry(tag.clone()).or_default().insert(key.as_str());
+        }
+        self.cache.insert(key.as_str(), value).await;
+        Ok(())
+    }
+
+    // Index entries of keys that expired are dropped here too
+    async fn invalidate_tag(&self, tag: &str) -> Result<u64> {
+        let mut index = self.tags.lock().await;
+        let Some(keys) = index.remove(tag) else {
+            return Ok(0);
+        };
+
+        let mut deleted = 0;
+        for key in keys {
+            if self.cache.remove(&key).await.is_some() {
+                deleted += 1;
+            }
+        }
+        Ok(deleted)
+    }
+
     async fn health_check(&self) -> Result<()> {
         Ok(())
     }
 }
 
+/// Parse a memory-backend window counter stored as `count:expires_at_ms`
+#[cfg(feature = "memory")]
+fn parse_window(value: &[u8]) -> Option<(i64, i64)> {
+    let (count, expires_at) = std::str::from_utf8(value).ok()?.split_once(':')?;
+    Some((count.parse().ok()?, expires_at.parse().ok()?))
+}
+
 /// Redis cache backend
 #[cfg(feature = "redis")]
 pub struct RedisBackend {
     pool: deadpool_redis::Pool,
+    url: String,
 }
 
 #[cfg(feature = "redis")]
@@ -172,7 +261,29 @@ impl RedisBackend {
                 message: format!("Failed to create Redis pool: {}", e),
             })?;
 
-        Ok(Self { pool })
+        Ok(Self {
+            pool,
+            url: url.to_string(),
+        })
+    }
+
+    /// URL the backend connects to
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Publish a message on a pub/sub channel
+    pub async fn publish(&self, channel: &str, message: &str) -> Result<()> {
+        let mut conn = self.get_connection().await?;
+        let _: i64 = redis::cmd("PUBLISH")
+            .arg(channel)
+            .arg(message)
+            .query_async(&mut *conn)
+            .await
+            .map_err(|e| Error::Cache {
+                message: format!("Redis PUBLISH failed: {}", e),
+            })?;
+        Ok(())
     }
 
     async fn get_connection(&self) -> Result<deadpool_redis::Connection> {
@@ -180,8 +291,59 @@ impl RedisBackend {
             message: format!("Failed to get Redis connection: {}", e),
         })
     }
+
+    /// Delete every entry set with `tag`, returning the deleted keys
+    pub async fn invalidate_tag_keys(&self, tag: &str) -> Result<Vec<String>> {
+        let mut conn = self.get_connection().await?;
+        redis::Script::new(INVALIDATE_TAG_SCRIPT)
+            .key(format!("{}{}", TAG_INDEX_PREFIX, tag))
+            .invoke_async(&mut *conn)
+            .await
+            .map_err(|e| Error::Cache {
+                message: format!("Redis tag invalidation failed: {}", e),
+            })
+    }
 }
 
+/// Set `KEYS[1]` to `ARGV[1]` (expiring after `ARGV[2]` seconds unless 0)
+/// and add it to the tag indexes `KEYS[2..]`. An index lives as long as
+/// its longest-lived entry.
+#[cfg(feature = "redis")]
+const SET_WITH_TAGS_SCRIPT: &str = r#"
+local ttl = tonumber(ARGV[2])
+if ttl > 0 then
+    redis.call('SET', KEYS[1], ARGV[1], 'EX', ttl)
+else
+    redis.call('SET', KEYS[1], ARGV[1])
+end
+for i = 2, #KEYS do
+    local fresh = redis.call('EXISTS', KEYS[i]) == 0
+    redis.call('SADD', KEYS[i], KEYS[1])
+    if ttl == 0 then
+        redis.call('PERSIST', KEYS[i])
+    else
+        local remaining = redis.call('TTL', KEYS[i])
+        if fresh or (remaining >= 0 and remaining < ttl) then
+            redis.call('EXPIRE', KEYS[i], ttl)
+        end
+    end
+end
+"#;
+
+/// Delete the entries in tag index `KEYS[1]` and the index itself,
+/// returning the keys that still existed
+#[cfg(feature = "redis")]
+const INVALIDATE_TAG_SCRIPT: &str = r#"
+local deleted = {}
+for _, key in ipairs(redis.call('SMEMBERS', KEYS[1])) do
+    if redis.call('DEL', key) == 1 then
+        table.insert(deleted, key)
+    end
+end
+redis.call('DEL', KEYS[1])
+return deleted
+"#;
+
 #[cfg(feature = "redis")]
 #[async_trait]
 impl CacheBackend for RedisBackend {
@@ -199,13 +361,15 @@ impl CacheBackend for RedisBackend {
         let mut conn = self.get_connection().await?;
 
         if let Some(ttl) = ttl {
-            conn.set_ex(key.as_str(), value, ttl.as_secs() as u64)
+            let _: () = conn
+                .set_ex(key.as_str(), value, ttl.as_secs())
                 .await
                 .map_err(|e| Error::Cache {
                     message: format!("Redis SETEX failed: {}", e),
                 })?;
         } else {
-            conn.set(key.as_str(), value)
+            let _: () = conn
+                .set(key.as_str(), value)
                 .await
                 .map_err(|e| Error::Cache {
                     message: format!("Redis SET failed: {}", e),
@@ -251,9 +415,8 @@ impl CacheBackend for RedisBackend {
     }
 
     async fn clear(&self) -> Result<()> {
-        use redis::AsyncCommands;
         let mut conn = self.get_connection().await?;
-        redis::cmd("FLUSHDB")
+        let _: () = redis::cmd("FLUSHDB")
             .query_async(&mut *conn)
             .await
             .map_err(|e| Error::Cache {
@@ -300,8 +463,59 @@ impl CacheBackend for RedisBackend {
         Ok(value)
     }
 
+    async fn increment_window(&self, key: &CacheKey, delta: i64, window: Duration) -> Result<i64> {
+        let mut conn = self.get_connection().await?;
+        // SET NX only creates the counter (with its expiry) if missing, and
+        // INCRBY keeps the existing TTL
+        let (value,): (i64,) = redis::pipe()
+            .atomic()
+            .cmd("SET")
+            .arg(key.as_str())
+            .arg(0)
+            .arg("NX")
+            .arg("PX")
+            .arg(window.as_millis().max(1) as u64)
+            .ignore()
+            .cmd("INCRBY")
+            .arg(key.as_str())
+            .arg(delta)
+            .query_async(&mut *conn)
+            .await
+            .map_err(|e| Error::Cache {
+                message: format!("Redis windowed INCRBY failed: {}", e),
+            })?;
+        Ok(value)
+    }
+
+    async fn set_with_tags(
+        &self,
+        key: &CacheKey,
+        value: Vec<u8>,
+        ttl: Option<Duration>,
+        tags: &[String],
+    ) -> Result<()> {
+        let mut conn = self.get_connection().await?;
+        let script = redis::Script::new(SET_WITH_TAGS_SCRIPT);
+        let mut invocation = script.key(key.as_str());
+        for tag in tags {
+            invocation.key(format!("{}{}", TAG_INDEX_PREFIX, tag));
+        }
+        let _: () = invocation
+            .arg(value)
+            .arg(ttl.map_or(0, |ttl| ttl.as_secs().max(1)))
+            .invoke_async(&mut *conn)
+            .await
+            .map_err(|e| Error::Cache {
+                message: format!("Redis tagged SET failed: {}", e),
+            })?;
+        Ok(())
+    }
+
+    async fn invalidate_tag(&self, tag: &str) -> Result<u64> {
+        Ok(self.invalidate_tag_keys(tag).await?.len() as u64)
+    }
+
     async fn health_check(&self) -> Result<()> {
-        use redis::AsyncCommands;
         let mut conn = self.get_connection().await?;
         let _: String = redis::cmd("PING")
             .query_async(&mut *conn)
@@ -354,11 +568,142 @@ impl CacheBackend for NullBackend {
         Ok(0)
     }
 
+    async fn increment_window(
+        &self,
+        _key: &CacheKey,
+        _delta: i64,
+        _window: Duration,
+    ) -> Result<i64> {
+        Ok(0)
+    }
+
+    async fn set_with_tags(
+        &self,
+        _key: &CacheKey,
+        _value: Vec<u8>,
+        _ttl: Option<Duration>,
+        _tags: &[String],
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    async fn invalidate_tag(&self, _tag: &str) -> Result<u64> {
+        Ok(0)
+    }
+
     async fn health_check(&self) -> Result<()> {
         Ok(())
     }
 }
 
+/// Backend wrapper that injects configured cache faults before each call
+pub struct FaultInjectingBackend {
+    inner: Arc<dyn CacheBackend>,
+    faults: FaultInjector,
+}
+
+impl FaultInjectingBackend {
+    pub fn new(inner: Arc<dyn CacheBackend>, faults: FaultInjector) -> Self {
+        Self { inner, faults }
+    }
+
+    async fn inject(&self) -> Result<()> {
+        self.faults.inject(FaultTarget::Cache, None).await
+    }
+}
+
+#[async_trait]
+impl CacheBackend for FaultInjectingBackend {
+    async fn get(&self, key: &CacheKey) -> Result<Option<Vec<u8>>> {
+        self.inject().await?;
+        self.inner.get(key).await
+    }
+
+    async fn set(&self, key: &CacheKey, value: Vec<u8>, ttl: Option<Duration>) -> Result<()> {
+        self.inject().await?;
+        self.inner.set(key, value, ttl).await
+    }
+
+    async fn delete(&self, key: &CacheKey) -> Result<bool> {
+        self.inject().await?;
+        self.inner.delete(key).await
+    }
+
+    async fn exists(&self, key: &CacheKey) -> Result<bool> {
+        self.inject().await?;
+        self.inner.exists(key).await
+    }
+
+    async fn delete_pattern(&self, pattern: &str) -> Result<u64> {
+        self.inject().await?;
+        self.inner.delete_pattern(pattern).await
+    }
+
+    async fn clear(&self) -> Result<()> {
+        self.inject().await?;
+        self.inner.clear().await
+    }
+
+    async fn ttl(&self, key: &CacheKey) -> Result<Option<Duration>> {
+        self.inject().await?;
+        self.inner.ttl(key).await
+    }
+
+    async fn increment(&self, key: &CacheKey, delta: i64) -> Result<i64> {
+        self.inject().await?;
+        self.inner.increment(key, delta).await
+    }
+
+    async fn decrement(&self, key: &CacheKey, delta: i64) -> Result<i64> {
+        self.inject().await?;
+        self.inner.decrement(key, delta).await
+    }
+
+    async fn increment_window(&self, key: &CacheKey, delta: i64, window: Duration) -> Result<i64> {
+        self.inject().await?;
+        self.inner.increment_window(key, delta, window).await
+    }
+
+    async fn set_with_tags(
+        &self,
+        key: &CacheKey,
+        value: Vec<u8>,
+        ttl: Option<Duration>,
+        tags: &[String],
+    ) -> Result<()> {
+        self.inject().await?;
+        self.inner.set_with_tags(key, value, ttl, tags).await
+    }
+
+    async fn invalidate_tag(&self, tag: &str) -> Result<u64> {
+        self.inject().await?;
+        self.inner.invalidate_tag(tag).await
+    }
+
+    async fn get_many(&self, keys: &[CacheKey]) -> Result<Vec<Option<Vec<u8>>>> {
+        self.inject().await?;
+        self.inner.get_many(keys).await
+    }
+
+    async fn set_many(&self, entries: &[(CacheKey, Vec<u8>)], ttl: Option<Duration>) -> Result<()> {
+        self.inject().await?;
+        self.inner.set_many(entries, ttl).await
+    }
+
+    async fn health_check(&self) -> Result<()> {
+        self.inject().await?;
+        self.inner.health_check().await
+    }
+
+    async fn stats(&self) -> CacheStats {
+        self.inner.stats().await
+    }
+
+    async fn list_keys(&self, prefix: Option<&str>) -> Vec<String> {
+        self.inner.list_keys(prefix).await
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -398,6 +743,43 @@ mod tests {
         assert_eq!(val, 6);
     }
 
+    #[cfg(feature = "memory")]
+    #[tokio::test]
+    async fn test_memory_tags() {
+        let backend = MemoryBackend::new(1000);
+        let tags = |tags: &[&str]| tags.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+        let post = CacheKey::new("post:1");
+        let archive = CacheKey::new("archive:2024");
+        let other = CacheKey::new("post:2");
+
+        backend
+            .set_with_tags(&post, b"post".to_vec(), None, &tags(&["post:1"]))
+            .await
+            .unwrap();
+        backend
+            .set_with_tags(
+                &archive,
+                b"list".to_vec(),
+                None,
+                &tags(&["post:1", "post:2"]),
+            )
+            .await
+            .unwrap();
+        backend
+            .set_with_tags(&other, b"post".to_vec(), None, &tags(&["post:2"]))
+            .await
+            .unwrap();
+
+        assert_eq!(backend.invalidate_tag("post:1").await.unwrap(), 2);
+        assert!(!backend.exists(&post).await.unwrap());
+        assert!(!backend.exists(&archive).await.unwrap());
+        assert!(backend.exists(&other).await.unwrap());
+
+        // The archive is already gone
+        assert_eq!(backend.invalidate_tag("post:2").await.unwrap(), 1);
+        assert_eq!(backend.invalidate_tag("post:2").await.unwrap(), 0);
+    }
+
     #[tokio::test]
     async fn test_null_backend() {
         let backend = NullBackend;
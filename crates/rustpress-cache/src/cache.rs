@@ -3,9 +3,11 @@
 use crate::backend::CacheBackend;
 use crate::key::CacheKey;
 use rustpress_core::error::{Error, Result};
-use serde::{de::DeserializeOwned, Serialize};
-use std::sync::Arc;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
+use tokio::sync::{Mutex as AsyncMutex, OwnedMutexGuard};
 
 /// Cache configuration
 #[derive(Debug, Clone)]
@@ -28,24 +30,78 @@ impl Default for CacheConfig {
     }
 }
 
+/// Stale-while-revalidate policy for [`Cache::get_swr`]
+#[derive(Debug, Clone, Copy)]
+pub struct SwrPolicy {
+    /// How long an entry is served without refreshing it
+    pub fresh_for: Duration,
+    /// How much longer an expired entry is served while it is refreshed
+    pub stale_for: Duration,
+}
+
+impl SwrPolicy {
+    pub fn new(fresh_for: Duration, stale_for: Duration) -> Self {
+        Self {
+            fresh_for,
+            stale_for,
+        }
+    }
+}
+
+/// Entry stored by [`Cache::get_swr`]
+#[derive(Serialize, Deserialize)]
+struct SwrEntry<T> {
+    value: T,
+    /// Unix milliseconds after which the entry is stale
+    fresh_until: i64,
+}
+
+/// Keys being refreshed, each with a lock held until its refresh ends
+type RefreshRegistry = Arc<Mutex<HashMap<String, Arc<AsyncMutex<()>>>>>;
+
+/// The refresh of one key; dropping it lets waiting readers through
+struct Refresh {
+    key: String,
+    registry: RefreshRegistry,
+    _running: OwnedMutexGuard<()>,
+}
+
+impl Drop for Refresh {
+    fn drop(&mut self) {
+        self.registry
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&self.key);
+    }
+}
+
+enum Flight {
+    /// This caller runs the refresh
+    Leader(Refresh),
+    /// Another caller is refreshing; the lock is free once it is done
+    Follower(Arc<AsyncMutex<()>>),
+}
+
 /// High-level cache interface
 pub struct Cache {
     backend: Arc<dyn CacheBackend>,
     config: CacheConfig,
+    refreshing: RefreshRegistry,
 }
 
 impl Cache {
     /// Create a new cache with the given backend
     pub fn new(backend: Arc<dyn CacheBackend>) -> Self {
-        Self {
-            backend,
-            config: CacheConfig::default(),
-        }
+        Self::with_config(backend, CacheConfig::default())
     }
 
     /// Create a cache with custom configuration
     pub fn with_config(backend: Arc<dyn CacheBackend>, config: CacheConfig) -> Self {
-        Self { backend, config }
+        Self {
+            backend,
+            config,
+            refreshing: Arc::default(),
+        }
     }
 
     /// Get the full key with prefix
@@ -163,6 +219,51 @@ impl Cache {
         self.backend.decrement(&key, delta).await
     }
 
+    /// Increment a fixed-window counter, e.g. for rate limits. The window
+    /// starts with the first increment and is not extended by later ones.
+    pub async fn increment_window(
+        &self,
+        key: impl Into<CacheKey>,
+        delta: i64,
+        window: Duration,
+    ) -> Result<i64> {
+        let key = self.full_key(&key.into());
+        self.backend.increment_window(&key, delta, window).await
+    }
+
+    /// Set a value tagged with `tags`, e.g. the ids of the posts a
+    /// rendered list shows; [`Cache::invalidate_tag`] drops it with every
+    /// other entry carrying the tag
+    pub async fn set_with_tags<T: Serialize>(
+        &self,
+        key: impl Into<CacheKey>,
+        value: &T,
+        ttl: Option<Duration>,
+        tags: &[String],
+    ) -> Result<()> {
+        let key = self.full_key(&key.into());
+        let bytes = serde_json::to_vec(value).map_err(|e| Error::Cache {
+            message: format!("Serialization failed: {}", e),
+        })?;
+        let tags: Vec<String> = tags.iter().map(|tag| self.full_tag(tag)).collect();
+
+        let ttl = ttl.or(Some(self.config.default_ttl));
+        self.backend.set_with_tags(&key, bytes, ttl, &tags).await
+    }
+
+    /// Delete every entry set with `tag`; returns how many were deleted
+    pub async fn invalidate_tag(&self, tag: &str) -> Result<u64> {
+        self.backend.invalidate_tag(&self.full_tag(tag)).await
+    }
+
+    /// Tags are namespaced by the key prefix like keys are
+    fn full_tag(&self, tag: &str) -> String {
+        match &self.config.prefix {
+            Some(prefix) => format!("{}:{}", prefix, tag),
+            None => tag.to_string(),
+        }
+    }
+
     /// Remember a value (get or compute and store)
     pub async fn remember<T, F, Fut>(
         &self,
@@ -178,6 +279,100 @@ impl Cache {
         self.get_or_set(key, Some(ttl), f).await
     }
 
+    /// Get a value with stale-while-revalidate semantics
+    ///
+    /// Fresh entries are returned as is. Stale entries are returned
+    /// immediately while `refresh` repopulates them in the background.
+    /// Misses wait for `refresh`. Only one refresh per key runs at a time:
+    /// concurrent misses wait for it instead of starting their own.
+    ///
+    /// Entries are stored with their freshness, so keys used here must
+    /// only be read through this method.
+    pub async fn get_swr<T, F, Fut>(
+        &self,
+        key: impl Into<CacheKey>,
+        policy: SwrPolicy,
+        refresh: F,
+    ) -> Result<T>
+    where
+        T: Serialize + DeserializeOwned + Send + 'static,
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: std::future::Future<Output = Result<T>> + Send + 'static,
+    {
+        let key = self.full_key(&key.into());
+
+        match self.read_swr::<T>(&key).await? {
+            Some(entry) if entry.fresh_until > chrono::Utc::now().timestamp_millis() => {
+                Ok(entry.value)
+            }
+            Some(entry) => {
+                if let Flight::Leader(flight) = self.join_refresh(&key) {
+                    let backend = self.backend.clone();
+                    let key = key.clone();
+                    tokio::spawn(async move {
+                        let result = match refresh().await.and_then(|v| encode_swr(&v, policy)) {
+                            Ok((bytes, ttl)) => backend.set(&key, bytes, Some(ttl)).await,
+                            Err(e) => Err(e),
+                        };
+                        drop(flight);
+                        if let Err(e) = result {
+                            tracing::warn!(key = %key, error = %e, "Cache refresh failed; serving stale entry");
+                        }
+                    });
+                }
+                Ok(entry.value)
+            }
+            None => {
+                let _flight = match self.join_refresh(&key) {
+                    Flight::Leader(flight) => Some(flight),
+                    Flight::Follower(running) => {
+                        drop(running.lock().await);
+                        if let Some(entry) = self.read_swr::<T>(&key).await? {
+                            return Ok(entry.value);
+                        }
+                        // The other refresh failed; try once more ourselves
+                        None
+                    }
+                };
+                let value = refresh().await?;
+                let (bytes, ttl) = encode_swr(&value, policy)?;
+                self.backend.set(&key, bytes, Some(ttl)).await?;
+                Ok(value)
+            }
+        }
+    }
+
+    async fn read_swr<T: DeserializeOwned>(&self, key: &CacheKey) -> Result<Option<SwrEntry<T>>> {
+        match self.backend.get(key).await? {
+            Some(bytes) => serde_json::from_slice(&bytes)
+                .map(Some)
+                .map_err(|e| Error::Cache {
+                    message: format!("Deserialization failed: {}", e),
+                }),
+            None => Ok(None),
+        }
+    }
+
+    /// Become the refresher of `key`, or get the lock of the running refresh
+    fn join_refresh(&self, key: &CacheKey) -> Flight {
+        let mut registry = self.refreshing.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(running) = registry.get(&key.as_str()) {
+            return Flight::Follower(running.clone());
+        }
+
+        let running = Arc::new(AsyncMutex::new(()));
+        let guard = running
+            .clone()
+            .try_lock_owned()
+            .expect("new lock is unlocked");
+        registry.insert(key.as_str().to_string(), running);
+        Flight::Leader(Refresh {
+            key: key.as_str().to_string(),
+            registry: self.refreshing.clone(),
+            _running: guard,
+        })
+    }
+
     /// Remember forever (no TTL)
     pub async fn remember_forever<T, F, Fut>(&self, key: impl Into<CacheKey>, f: F) -> Result<T>
     where
@@ -270,6 +465,16 @@ impl Cache {
     }
 }
 
+/// Serialize a stale-while-revalidate entry and the TTL to store it with
+fn encode_swr<T: Serialize>(value: &T, policy: SwrPolicy) -> Result<(Vec<u8>, Duration)> {
+    let fresh_until = chrono::Utc::now().timestamp_millis()
+        + i64::try_from(policy.fresh_for.as_millis()).unwrap_or(i64::MAX / 2);
+    let bytes = serde_json::to_vec(&SwrEntry { value, fresh_until }).map_err(|e| Error::Cache {
+        message: format!("Serialization failed: {}", e),
+    })?;
+    Ok((bytes, policy.fresh_for + policy.stale_for))
+}
+
 // Re-export CacheStats from crate root
 pub use crate::CacheStats;
 
@@ -380,6 +585,105 @@ mod tests {
         assert_eq!(val, 6);
     }
 
+    #[tokio::test]
+    async fn test_increment_window_keeps_expiry() {
+        let cache = create_test_cache();
+        let window = Duration::from_millis(200);
+
+        assert_eq!(cache.increment_window("rate", 1, window).await.unwrap(), 1);
+        tokio::time::sleep(Duration::from_millis(120)).await;
+        assert_eq!(cache.increment_window("rate", 1, window).await.unwrap(), 2);
+
+        // The second increment did not extend the window
+        tokio::time::sleep(Duration::from_millis(120)).await;
+        assert_eq!(cache.increment_window("rate", 1, window).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_swr_serves_stale_while_refreshing() {
+        let cache = create_test_cache();
+        let policy = SwrPolicy::new(Duration::from_millis(50), Duration::from_secs(60));
+
+        let value: u32 = cache
+            .get_swr("widget", policy, || async { Ok(1) })
+            .await
+            .unwrap();
+        assert_eq!(value, 1);
+
+        // Fresh entries are not refreshed
+        let value: u32 = cache
+            .get_swr("widget", policy, || async { Ok(2) })
+            .await
+            .unwrap();
+        assert_eq!(value, 1);
+
+        // Stale entries are served while the refresh runs
+        tokio::time::sleep(Duration::from_millis(80)).await;
+        let value: u32 = cache
+            .get_swr("widget", policy, || async { Ok(3) })
+            .await
+            .unwrap();
+        assert_eq!(value, 1);
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let value: u32 = cache
+            .get_swr("widget", policy, || async { Ok(4) })
+            .await
+            .unwrap();
+        assert_eq!(value, 3);
+    }
+
+    #[tokio::test]
+    async fn test_swr_single_refresh_per_key() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let cache = create_test_cache();
+        let policy = SwrPolicy::new(Duration::from_secs(60), Duration::from_secs(60));
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let load = || {
+            let calls = calls.clone();
+            cache.get_swr("stats", policy, move || async move {
+                calls.fetch_add(1, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                Ok(42u32)
+            })
+        };
+        let (a, b, c) = tokio::join!(load(), load(), load());
+
+        assert_eq!((a.unwrap(), b.unwrap(), c.unwrap()), (42, 42, 42));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_swr_failed_refresh_keeps_stale_entry() {
+        let cache = create_test_cache();
+        let policy = SwrPolicy::new(Duration::from_millis(20), Duration::from_secs(60));
+
+        let _: u32 = cache
+            .get_swr("flaky", policy, || async { Ok(1) })
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(40)).await;
+
+        let value: u32 = cache
+            .get_swr("flaky", policy, || async {
+                Err(Error::Cache {
+                    message: "upstream down".to_string(),
+                })
+            })
+            .await
+            .unwrap();
+        assert_eq!(value, 1);
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let value: u32 = cache
+            .get_swr("flaky", policy, || async { Ok(2) })
+            .await
+            .unwrap();
+        assert_eq!(value, 1);
+    }
+
     #[tokio::test]
     async fn test_complex_types() {
         let cache = create_test_cache();
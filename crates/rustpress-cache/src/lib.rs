@@ -5,13 +5,17 @@
 pub mod backend;
 pub mod cache;
 pub mod key;
+#[cfg(all(feature = "memory", feature = "redis"))]
+pub mod tiered;
 
-pub use backend::{CacheBackend, MemoryBackend};
-pub use cache::{Cache, CacheConfig};
+pub use backend::{CacheBackend, FaultInjectingBackend, MemoryBackend};
+pub use cache::{Cache, CacheConfig, SwrPolicy};
 pub use key::CacheKey;
 
 #[cfg(feature = "redis")]
 pub use backend::RedisBackend;
+#[cfg(all(feature = "memory", feature = "redis"))]
+pub use tiered::{TieredBackend, TieredConfig};
 
 /// Cache statistics
 #[derive(Debug, Clone, Default)]
@@ -0,0 +1,361 @@
+//! Two-tier cache backend.
+//!
+//! Layers an in-process [`MemoryBackend`] (L1) over a shared
+//! [`RedisBackend`] (L2). Reads are served from L1 when possible and fill
+//! it from L2 on a miss; writes go through to L2 first. Every write and
+//! delete is announced on a Redis pub/sub channel so the other nodes drop
+//! their L1 copy, which keeps the near cache consistent across nodes while
+//! most reads never leave the process.
+
+use crate::backend::{CacheBackend, MemoryBackend, RedisBackend};
+use crate::key::CacheKey;
+use crate::CacheStats;
+use async_trait::async_trait;
+use futures::StreamExt;
+use rustpress_core::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+/// Default pub/sub channel for near-cache invalidation
+pub const INVALIDATION_CHANNEL: &str = "rustpress:cache:invalidate";
+
+/// Longest wait between reconnects of the invalidation listener
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// Tiered cache configuration
+#[derive(Debug, Clone)]
+pub struct TieredConfig {
+    /// Entries kept in the in-process tier
+    pub l1_capacity: u64,
+    /// Longest an entry stays in the in-process tier. Keeping this short
+    /// bounds staleness should an invalidation message be lost.
+    pub l1_ttl: Duration,
+    /// TTL in Redis for writes that do not specify one
+    pub l2_ttl: Option<Duration>,
+    /// Pub/sub channel carrying invalidations between nodes
+    pub channel: String,
+}
+
+impl Default for TieredConfig {
+    fn default() -> Self {
+        Self {
+            l1_capacity: 10_000,
+            l1_ttl: Duration::from_secs(60),
+            l2_ttl: None,
+            channel: INVALIDATION_CHANNEL.to_string(),
+        }
+    }
+}
+
+/// Invalidation broadcast to the other nodes
+#[derive(Debug, Serialize, Deserialize)]
+struct Invalidation {
+    /// Node that made the change; it has already updated its own L1
+    origin: String,
+    /// Keys to drop, or `None` to drop everything (pattern deletes, clears)
+    keys: Option<Vec<String>>,
+}
+
+/// Memory (L1) over Redis (L2) cache backend with write-through
+pub struct TieredBackend {
+    l1: MemoryBackend,
+    l2: Arc<RedisBackend>,
+    config: TieredConfig,
+    node_id: String,
+}
+
+impl TieredBackend {
+    /// Connect to Redis and start listening for invalidations
+    pub async fn connect(redis_url: &str, config: TieredConfig) -> Result<Arc<Self>> {
+        let l2 = Arc::new(RedisBackend::new(redis_url).await?);
+        let backend = Arc::new(Self::new(l2, config));
+        backend.spawn_invalidation_listener();
+        Ok(backend)
+    }
+
+    /// Layer a fresh in-process tier over `l2`. Call
+    /// [`spawn_invalidation_listener`](Self::spawn_invalidation_listener)
+    /// once the backend is shared.
+    pub fn new(l2: Arc<RedisBackend>, config: TieredConfig) -> Self {
+        Self {
+            l1: MemoryBackend::with_ttl(config.l1_capacity, config.l1_ttl),
+            l2,
+            node_id: format!(
+                "{}-{}",
+                std::process::id(),
+                chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default()
+            ),
+            config,
+        }
+    }
+
+    pub fn config(&self) -> &TieredConfig {
+        &self.config
+    }
+
+    /// Drop L1 entries as other nodes announce changes
+    ///
+    /// The listener reconnects with backoff and stops once the backend is
+    /// dropped. L1 is flushed after every reconnect, since invalidations
+    /// sent while disconnected are lost.
+    pub fn spawn_invalidation_listener(self: &Arc<Self>) -> tokio::task::JoinHandle<()> {
+        let backend = Arc::downgrade(self);
+        let url = self.l2.url().to_string();
+        let channel = self.config.channel.clone();
+
+        tokio::spawn(async move {
+            let mut delay = Duration::from_secs(1);
+            loop {
+                match listen(&backend, &url, &channel).await {
+                    Ok(()) => return,
+                    Err(e) => {
+                        tracing::warn!("Cache invalidation listener disconnected: {}", e);
+                    }
+                }
+                tokio::time::sleep(delay).await;
+                delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+
+                let Some(backend) = backend.upgrade() else {
+                    return;
+                };
+                let _ = backend.l1.clear().await;
+            }
+        })
+    }
+
+    /// Apply an invalidation received from the channel
+    async fn apply(&self, payload: &str) {
+        let Ok(invalidation) = serde_json::from_str::<Invalidation>(payload) else {
+            tracing::debug!("Ignoring malformed cache invalidation");
+            return;
+        };
+        if invalidation.origin == self.node_id {
+            return;
+        }
+        match invalidation.keys {
+            Some(keys) => {
+                for key in keys {
+                    let _ = self.l1.delete(&CacheKey::new(key)).await;
+                }
+            }
+            None => {
+                let _ = self.l1.clear().await;
+            }
+        }
+    }
+
+    /// Tell the other nodes to drop `keys` (or everything) from their L1
+    async fn broadcast(&self, keys: Option<Vec<String>>) {
+        let invalidation = Invalidation {
+            origin: self.node_id.clone(),
+            keys,
+        };
+        let Ok(payload) = serde_json::to_string(&invalidation) else {
+            return;
+        };
+        // Other nodes fall back to the L1 TTL if this is lost
+        if let Err(e) = self.l2.publish(&self.config.channel, &payload).await {
+            tracing::warn!("Failed to publish cache invalidation: {}", e);
+        }
+    }
+
+    /// Store in L1 for at most the L1 TTL, and never past the L2 expiry
+    async fn fill_l1(&self, key: &CacheKey, value: Vec<u8>, ttl: Option<Duration>) {
+        let ttl = ttl.map_or(self.config.l1_ttl, |ttl| ttl.min(self.config.l1_ttl));
+        let _ = self.l1.set(key, encode_l1(value, ttl), None).await;
+    }
+}
+
+/// Subscribe to the invalidation channel and apply messages until the
+/// connection drops (`Err`) or the backend is gone (`Ok`)
+async fn listen(backend: &Weak<TieredBackend>, url: &str, channel: &str) -> Result<()> {
+    let cache_error = |e: redis::RedisError| Error::Cache {
+        message: format!("Redis pub/sub failed: {}", e),
+    };
+    let client = redis::Client::open(url).map_err(cache_error)?;
+    let mut pubsub = client
+        .get_async_connection()
+        .await
+        .map_err(cache_error)?
+        .into_pubsub();
+    pubsub.subscribe(channel).await.map_err(cache_error)?;
+
+    let mut messages = pubsub.on_message();
+    while let Some(message) = messages.next().await {
+        let Some(backend) = backend.upgrade() else {
+            return Ok(());
+        };
+        if let Ok(payload) = message.get_payload::<String>() {
+            backend.apply(&payload).await;
+        }
+    }
+
+    Err(Error::Cache {
+        message: "subscription closed".to_string(),
+    })
+}
+
+/// Prefix an L1 value with its expiry (milliseconds since the epoch)
+fn encode_l1(value: Vec<u8>, ttl: Duration) -> Vec<u8> {
+    let expires_at = chrono::Utc::now().timestamp_millis() + ttl.as_millis() as i64;
+    let mut encoded = Vec::with_capacity(value.len() + 8);
+    encoded.extend_from_slice(&expires_at.to_be_bytes());
+    encoded.extend_from_slice(&value);
+    encoded
+}
+
+/// Value of an L1 entry, or `None` once it has expired
+fn decode_l1(encoded: Vec<u8>) -> Option<Vec<u8>> {
+    let expires_at = i64::from_be_bytes(encoded.get(..8)?.try_into().ok()?);
+    if expires_at <= chrono::Utc::now().timestamp_millis() {
+        return None;
+    }
+    Some(encoded[8..].to_vec())
+}
+
+#[async_trait]
+impl CacheBackend for TieredBackend {
+    async fn get(&self, key: &CacheKey) -> Result<Option<Vec<u8>>> {
+        if let Some(encoded) = self.l1.get(key).await? {
+            match decode_l1(encoded) {
+                Some(value) => return Ok(Some(value)),
+                None => {
+                    self.l1.delete(key).await?;
+                }
+            }
+        }
+
+        let Some(value) = self.l2.get(key).await? else {
+            return Ok(None);
+        };
+        let ttl = self.l2.ttl(key).await.unwrap_or(None);
+        self.fill_l1(key, value.clone(), ttl).await;
+        Ok(Some(value))
+    }
+
+    async fn set(&self, key: &CacheKey, value: Vec<u8>, ttl: Option<Duration>) -> Result<()> {
+        let ttl = ttl.or(self.config.l2_ttl);
+        self.l2.set(key, value.clone(), ttl).await?;
+        self.fill_l1(key, value, ttl).await;
+        self.broadcast(Some(vec![key.as_str()])).await;
+        Ok(())
+    }
+
+    async fn delete(&self, key: &CacheKey) -> Result<bool> {
+        let deleted = self.l2.delete(key).await?;
+        self.l1.delete(key).await?;
+        self.broadcast(Some(vec![key.as_str()])).await;
+        Ok(deleted)
+    }
+
+    async fn exists(&self, key: &CacheKey) -> Result<bool> {
+        if let Some(encoded) = self.l1.get(key).await? {
+            if decode_l1(encoded).is_some() {
+                return Ok(true);
+            }
+        }
+        self.l2.exists(key).await
+    }
+
+    // The memory tier cannot match patterns, so it is flushed instead
+    async fn delete_pattern(&self, pattern: &str) -> Result<u64> {
+        let deleted = self.l2.delete_pattern(pattern).await?;
+        self.l1.clear().await?;
+        self.broadcast(None).await;
+        Ok(deleted)
+    }
+
+    async fn clear(&self) -> Result<()> {
+        self.l2.clear().await?;
+        self.l1.clear().await?;
+        self.broadcast(None).await;
+        Ok(())
+    }
+
+    async fn ttl(&self, key: &CacheKey) -> Result<Option<Duration>> {
+        self.l2.ttl(key).await
+    }
+
+    // Counters live in Redis only, so every node sees the same value
+    async fn increment(&self, key: &CacheKey, delta: i64) -> Result<i64> {
+        self.l2.increment(key, delta).await
+    }
+
+    async fn decrement(&self, key: &CacheKey, delta: i64) -> Result<i64> {
+        self.l2.decrement(key, delta).await
+    }
+
+    async fn increment_window(&self, key: &CacheKey, delta: i64, window: Duration) -> Result<i64> {
+        self.l2.increment_window(key, delta, window).await
+    }
+
+    async fn set_with_tags(
+        &self,
+        key: &CacheKey,
+        value: Vec<u8>,
+        ttl: Option<Duration>,
+        tags: &[String],
+    ) -> Result<()> {
+        let ttl = ttl.or(self.config.l2_ttl);
+        self.l2.set_with_tags(key, value.clone(), ttl, tags).await?;
+        self.fill_l1(key, value, ttl).await;
+        self.broadcast(Some(vec![key.as_str()])).await;
+        Ok(())
+    }
+
+    // Tags are only indexed in Redis; it reports which keys to drop here
+    async fn invalidate_tag(&self, tag: &str) -> Result<u64> {
+        let keys = self.l2.invalidate_tag_keys(tag).await?;
+        for key in &keys {
+            self.l1.delete(&CacheKey::new(key.clone())).await?;
+        }
+        let deleted = keys.len() as u64;
+        self.broadcast(Some(keys)).await;
+        Ok(deleted)
+    }
+
+    async fn health_check(&self) -> Result<()> {
+        self.l2.health_check().await
+    }
+
+    async fn stats(&self) -> CacheStats {
+        self.l1.stats().await
+    }
+
+    async fn list_keys(&self, prefix: Option<&str>) -> Vec<String> {
+        self.l1.list_keys(prefix).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_l1_envelope() {
+        let encoded = encode_l1(b"hello".to_vec(), Duration::from_secs(60));
+        assert_eq!(decode_l1(encoded), Some(b"hello".to_vec()));
+
+        let expired = encode_l1(b"hello".to_vec(), Duration::ZERO);
+        assert_eq!(decode_l1(expired), None);
+        assert_eq!(decode_l1(vec![1, 2, 3]), None);
+    }
+
+    #[tokio::test]
+    async fn test_apply_invalidation() {
+        let l2 = Arc::new(RedisBackend::new("redis://127.0.0.1:6379").await.unwrap());
+        let backend = TieredBackend::new(l2, TieredConfig::default());
+        let key = CacheKey::new("post:1");
+        backend.fill_l1(&key, b"cached".to_vec(), None).await;
+
+        // Own messages are ignored
+        let own = serde_json::json!({ "origin": backend.node_id, "keys": ["post:1"] });
+        backend.apply(&own.to_string()).await;
+        assert!(backend.l1.exists(&key).await.unwrap());
+
+        let other = serde_json::json!({ "origin": "other-node", "keys": ["post:1"] });
+        backend.apply(&other.to_string()).await;
+        assert!(!backend.l1.exists(&key).await.unwrap());
+    }
+}
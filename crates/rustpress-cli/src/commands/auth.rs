@@ -5,7 +5,8 @@ use serde::{Deserialize, Serialize};
 
 use crate::context::{CliContext, CliCredentials};
 use crate::error::{CliError, CliResult};
-use crate::output::{print_header, print_kv, OutputFormatter};
+use crate::output::{human, print_header, print_kv};
+use tabled::Tabled;
 
 #[derive(Args, Debug)]
 pub struct AuthCommand {
@@ -26,6 +27,9 @@ pub enum AuthSubcommand {
         /// Server URL (default: http://localhost:3080)
         #[arg(short, long)]
         server: Option<String>,
+        /// Approve the login in a browser instead of entering a password
+        #[arg(long, conflicts_with_all = ["email", "password"])]
+        device: bool,
     },
     /// Logout and clear stored credentials
     Logout,
@@ -49,6 +53,31 @@ pub enum AuthSubcommand {
         #[arg(long)]
         show: bool,
     },
+    /// Manage API keys for scripts and --remote
+    #[command(subcommand, name = "api-keys", alias = "api-key")]
+    ApiKeys(ApiKeysSubcommand),
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ApiKeysSubcommand {
+    /// List your API keys
+    List,
+    /// Create an API key; its secret is shown only once
+    Create {
+        /// Name to tell the key apart
+        name: String,
+        /// Scope to grant, e.g. posts:read or *:write (repeatable; default *:*)
+        #[arg(long = "scope", value_name = "RESOURCE:ACTION")]
+        scopes: Vec<String>,
+        /// Days until the key expires (default: never)
+        #[arg(long)]
+        expires_in_days: Option<u32>,
+    },
+    /// Revoke an API key
+    Revoke {
+        /// Key ID
+        id: String,
+    },
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -65,6 +94,116 @@ struct LoginResponse {
     expires_in: Option<i64>,
 }
 
+/// Client ID the server registers for the CLI's device login
+const DEVICE_CLIENT_ID: &str = "rustpress-cli";
+
+#[derive(Debug, Serialize)]
+struct DeviceCodeRequest<'a> {
+    client_id: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeviceCodeResponse {
+    device_code: String,
+    user_code: String,
+    verification_uri: String,
+    verification_uri_complete: Option<String>,
+    expires_in: u64,
+    interval: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct DeviceTokenRequest<'a> {
+    client_id: &'a str,
+    device_code: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeviceTokenError {
+    error: String,
+    error_description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeviceTokenUser {
+    email: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeviceTokenResponse {
+    access_token: String,
+    refresh_token: Option<String>,
+    user: DeviceTokenUser,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiEnvelope<T> {
+    data: T,
+}
+
+#[derive(Debug, Serialize)]
+struct CreateApiKeyRequest {
+    name: String,
+    scopes: Vec<String>,
+    expires_in_days: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ApiKey {
+    id: String,
+    name: String,
+    key_prefix: String,
+    scopes: Vec<String>,
+    expires_at: Option<String>,
+    last_used_at: Option<String>,
+    revoked_at: Option<String>,
+    created_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CreatedApiKey {
+    #[serde(flatten)]
+    key: ApiKey,
+    secret: String,
+}
+
+#[derive(Debug, Serialize, Tabled)]
+struct ApiKeyRow {
+    #[tabled(rename = "ID")]
+    id: String,
+    #[tabled(rename = "Name")]
+    name: String,
+    #[tabled(rename = "Prefix")]
+    prefix: String,
+    #[tabled(rename = "Scopes")]
+    scopes: String,
+    #[tabled(rename = "Expires")]
+    expires: String,
+    #[tabled(rename = "Last Used")]
+    last_used: String,
+    #[tabled(rename = "Status")]
+    status: String,
+}
+
+impl From<ApiKey> for ApiKeyRow {
+    fn from(key: ApiKey) -> Self {
+        Self {
+            id: key.id,
+            name: key.name,
+            prefix: format!("{}...", key.key_prefix),
+            scopes: key.scopes.join(", "),
+            expires: key.expires_at.unwrap_or_else(|| "never".to_string()),
+            last_used: key.last_used_at.unwrap_or_else(|| "-".to_string()),
+            status: if key.revoked_at.is_some() {
+                "revoked"
+            } else {
+                "active"
+            }
+            .to_string(),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct UserInfo {
     id: String,
@@ -79,11 +218,27 @@ pub async fn execute(ctx: &CliContext, cmd: AuthCommand) -> CliResult<()> {
             email,
             password,
             server,
-        } => login(ctx, email, password, server).await,
+            device,
+        } => {
+            if device {
+                device_login(ctx, server).await
+            } else {
+                login(ctx, email, password, server).await
+            }
+        }
         AuthSubcommand::Logout => logout(ctx).await,
         AuthSubcommand::Whoami => whoami(ctx).await,
         AuthSubcommand::Token { show, refresh } => manage_token(ctx, show, refresh).await,
         AuthSubcommand::Config { server, show } => configure(ctx, server, show).await,
+        AuthSubcommand::ApiKeys(sub) => match sub {
+            ApiKeysSubcommand::List => list_api_keys(ctx).await,
+            ApiKeysSubcommand::Create {
+                name,
+                scopes,
+                expires_in_days,
+            } => create_api_key(ctx, name, scopes, expires_in_days).await,
+            ApiKeysSubcommand::Revoke { id } => revoke_api_key(ctx, &id).await,
+        },
     }
 }
 
@@ -168,11 +323,109 @@ async fn login(
     };
     creds.save()?;
 
-    println!(
-        "{}",
-        ctx.output_format
-            .success(&format!("Logged in as {} on {}", email, server_url))
+    ctx.success(&format!("Logged in as {} on {}", email, server_url));
+    Ok(())
+}
+
+/// Log in with the OAuth2 device authorization grant: show a code, then
+/// poll until it is approved in a browser
+async fn device_login(ctx: &CliContext, server: Option<String>) -> CliResult<()> {
+    print_header("Login to RustPress");
+
+    let server_url = server.unwrap_or_else(|| {
+        std::env::var("RUSTPRESS_SERVER_URL")
+            .unwrap_or_else(|_| "http://localhost:3080".to_string())
+    });
+
+    let client = reqwest::Client::new();
+    let response = client
+        .post(format!("{}/api/v1/auth/device/code", server_url))
+        .json(&DeviceCodeRequest {
+            client_id: DEVICE_CLIENT_ID,
+        })
+        .send()
+        .await
+        .map_err(|e| CliError::Network(format!("Failed to connect to server: {}", e)))?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(CliError::Auth(format!(
+            "Device login failed ({}): {}",
+            status, body
+        )));
+    }
+
+    let device: DeviceCodeResponse = response
+        .json()
+        .await
+        .map_err(|e| CliError::Serialization(format!("Failed to parse device code: {}", e)))?;
+
+    print_kv(
+        "Open",
+        device
+            .verification_uri_complete
+            .as_deref()
+            .unwrap_or(&device.verification_uri),
     );
+    print_kv("Code", &device.user_code);
+    human!();
+    ctx.info("Waiting for the login to be approved in your browser...");
+
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(device.expires_in);
+    let mut interval = device.interval.max(1);
+
+    let tokens = loop {
+        if std::time::Instant::now() >= deadline {
+            return Err(CliError::Auth(
+                "The code expired before it was approved. Please try again.".to_string(),
+            ));
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(interval)).await;
+
+        let response = client
+            .post(format!("{}/api/v1/auth/device/token", server_url))
+            .json(&DeviceTokenRequest {
+                client_id: DEVICE_CLIENT_ID,
+                device_code: &device.device_code,
+            })
+            .send()
+            .await
+            .map_err(|e| CliError::Network(format!("Failed to poll for token: {}", e)))?;
+
+        if response.status().is_success() {
+            break response.json::<DeviceTokenResponse>().await.map_err(|e| {
+                CliError::Serialization(format!("Failed to parse login response: {}", e))
+            })?;
+        }
+
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        match serde_json::from_str::<DeviceTokenError>(&body) {
+            Ok(err) if err.error == "authorization_pending" => {}
+            Ok(err) if err.error == "slow_down" => interval += 5,
+            Ok(err) => return Err(CliError::Auth(err.error_description.unwrap_or(err.error))),
+            Err(_) => {
+                return Err(CliError::Auth(format!(
+                    "Device login failed ({}): {}",
+                    status, body
+                )))
+            }
+        }
+    };
+
+    let creds = CliCredentials {
+        server_url: server_url.clone(),
+        access_token: Some(tokens.access_token),
+        refresh_token: tokens.refresh_token,
+        email: Some(tokens.user.email.clone()),
+    };
+    creds.save()?;
+
+    ctx.success(&format!(
+        "Logged in as {} on {}",
+        tokens.user.email, server_url
+    ));
     Ok(())
 }
 
@@ -181,16 +434,28 @@ async fn logout(ctx: &CliContext) -> CliResult<()> {
 
     let creds = CliCredentials::load();
     if creds.access_token.is_none() {
-        println!("{}", ctx.output_format.info("Not currently logged in"));
+        ctx.info("Not currently logged in");
         return Ok(());
     }
 
     CliCredentials::clear()?;
-    println!("{}", ctx.output_format.success("Successfully logged out"));
+    ctx.success("Successfully logged out");
     Ok(())
 }
 
 async fn whoami(ctx: &CliContext) -> CliResult<()> {
+    if ctx.is_remote() {
+        ctx.require_auth()?;
+        let capabilities = ctx.capabilities().await?;
+        print_header("Current User");
+        print_kv("Email", capabilities.email.as_deref().unwrap_or("-"));
+        print_kv("Server", ctx.server_url());
+        print_kv("Roles", &capabilities.roles.join(", "));
+        print_kv("Auth", &capabilities.auth);
+        print_kv("Scopes", &capabilities.scopes.join(", "));
+        return Ok(());
+    }
+
     let creds = CliCredentials::load();
 
     match (&creds.access_token, &creds.email) {
@@ -219,30 +484,19 @@ async fn whoami(ctx: &CliContext) -> CliResult<()> {
                     }
                 }
                 Ok(response) if response.status() == reqwest::StatusCode::UNAUTHORIZED => {
-                    println!();
-                    println!(
-                        "{}",
-                        ctx.output_format.warning(
-                            "Token may be expired. Run 'rustpress auth login' to re-authenticate."
-                        )
+                    human!();
+                    ctx.warning(
+                        "Token may be expired. Run 'rustpress auth login' to re-authenticate.",
                     );
                 }
                 _ => {
-                    println!();
-                    println!(
-                        "{}",
-                        ctx.output_format
-                            .warning("Could not fetch user details from server")
-                    );
+                    human!();
+                    ctx.warning("Could not fetch user details from server");
                 }
             }
         }
         _ => {
-            println!(
-                "{}",
-                ctx.output_format
-                    .info("Not logged in. Run 'rustpress auth login' to authenticate.")
-            );
+            ctx.info("Not logged in. Run 'rustpress auth login' to authenticate.");
         }
     }
 
@@ -288,10 +542,7 @@ async fn manage_token(ctx: &CliContext, show: bool, refresh: bool) -> CliResult<
         }
         creds.save()?;
 
-        println!(
-            "{}",
-            ctx.output_format.success("Token refreshed successfully")
-        );
+        ctx.success("Token refreshed successfully");
         return Ok(());
     }
 
@@ -309,11 +560,7 @@ async fn manage_token(ctx: &CliContext, show: bool, refresh: bool) -> CliResult<
                 print_kv("Server", &creds.server_url);
             }
             None => {
-                println!(
-                    "{}",
-                    ctx.output_format
-                        .info("No token stored. Run 'rustpress auth login' to authenticate.")
-                );
+                ctx.info("No token stored. Run 'rustpress auth login' to authenticate.");
             }
         }
     }
@@ -327,11 +574,7 @@ async fn configure(ctx: &CliContext, server: Option<String>, _show: bool) -> Cli
     if let Some(server_url) = server {
         creds.server_url = server_url.clone();
         creds.save()?;
-        println!(
-            "{}",
-            ctx.output_format
-                .success(&format!("Server URL set to: {}", server_url))
-        );
+        ctx.success(&format!("Server URL set to: {}", server_url));
         return Ok(());
     }
 
@@ -360,3 +603,114 @@ async fn configure(ctx: &CliContext, server: Option<String>, _show: bool) -> Cli
 
     Ok(())
 }
+
+/// Error for a failed API key request
+async fn api_key_error(action: &str, response: reqwest::Response) -> CliError {
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    match status {
+        reqwest::StatusCode::UNAUTHORIZED | reqwest::StatusCode::FORBIDDEN => {
+            CliError::Auth(format!("Failed to {} ({}): {}", action, status, body))
+        }
+        reqwest::StatusCode::NOT_FOUND => CliError::NotFound(format!("API key ({})", body)),
+        _ => CliError::OperationFailed(format!("Failed to {} ({}): {}", action, status, body)),
+    }
+}
+
+async fn list_api_keys(ctx: &CliContext) -> CliResult<()> {
+    print_header("API Keys");
+
+    let url = format!("{}/api/v1/auth/api-keys", ctx.server_url());
+    let response = ctx
+        .http_client()
+        .get(&url)
+        .header("Authorization", ctx.auth_header()?)
+        .send()
+        .await
+        .map_err(|e| CliError::Network(format!("Failed to fetch API keys: {}", e)))?;
+
+    if !response.status().is_success() {
+        return Err(api_key_error("list API keys", response).await);
+    }
+
+    let keys: ApiEnvelope<Vec<ApiKey>> = response
+        .json()
+        .await
+        .map_err(|e| CliError::Serialization(format!("Failed to parse response: {}", e)))?;
+    let rows: Vec<ApiKeyRow> = keys.data.into_iter().map(ApiKeyRow::from).collect();
+
+    ctx.list(
+        &rows,
+        "No API keys. Create one with 'rustpress auth api-keys create <name>'.",
+    );
+    Ok(())
+}
+
+async fn create_api_key(
+    ctx: &CliContext,
+    name: String,
+    scopes: Vec<String>,
+    expires_in_days: Option<u32>,
+) -> CliResult<()> {
+    print_header("Creating API Key");
+
+    let url = format!("{}/api/v1/auth/api-keys", ctx.server_url());
+    let response = ctx
+        .http_client()
+        .post(&url)
+        .header("Authorization", ctx.auth_header()?)
+        .json(&CreateApiKeyRequest {
+            name,
+            scopes,
+            expires_in_days,
+        })
+        .send()
+        .await
+        .map_err(|e| CliError::Network(format!("Failed to create API key: {}", e)))?;
+
+    if !response.status().is_success() {
+        return Err(api_key_error("create API key", response).await);
+    }
+
+    let created: ApiEnvelope<CreatedApiKey> = response
+        .json()
+        .await
+        .map_err(|e| CliError::Serialization(format!("Failed to parse response: {}", e)))?;
+    let created = created.data;
+
+    print_kv("ID", &created.key.id);
+    print_kv("Name", &created.key.name);
+    print_kv("Scopes", &created.key.scopes.join(", "));
+    print_kv(
+        "Expires",
+        created.key.expires_at.as_deref().unwrap_or("never"),
+    );
+    print_kv("Key", &created.secret);
+    human!();
+    ctx.warning("Store the key now; it will not be shown again.");
+    human!(
+        "  Use it with: rustpress --remote {} --api-key <KEY> <command>",
+        ctx.server_url()
+    );
+    Ok(())
+}
+
+async fn revoke_api_key(ctx: &CliContext, id: &str) -> CliResult<()> {
+    ctx.confirm_remote(&format!("revoke API key {}", id))?;
+
+    let url = format!("{}/api/v1/auth/api-keys/{}", ctx.server_url(), id);
+    let response = ctx
+        .http_client()
+        .delete(&url)
+        .header("Authorization", ctx.auth_header()?)
+        .send()
+        .await
+        .map_err(|e| CliError::Network(format!("Failed to revoke API key: {}", e)))?;
+
+    if !response.status().is_success() {
+        return Err(api_key_error("revoke API key", response).await);
+    }
+
+    ctx.success(&format!("Revoked API key: {}", id));
+    Ok(())
+}
@@ -6,7 +6,7 @@ use tabled::Tabled;
 
 use crate::context::CliContext;
 use crate::error::CliResult;
-use crate::output::{print_header, print_kv, OutputFormatter, ProgressBar};
+use crate::output::{print_header, print_kv, ProgressBar};
 
 #[derive(Args, Debug)]
 pub struct BackupCommand {
@@ -111,11 +111,7 @@ async fn create_backup(
 
     let output_file = output
         .unwrap_or_else(|| format!("backup_{}.sql", chrono::Utc::now().format("%Y%m%d_%H%M%S")));
-    println!(
-        "{}",
-        ctx.output_format
-            .success(&format!("Backup created: {}", output_file))
-    );
+    ctx.success(&format!("Backup created: {}", output_file));
     Ok(())
 }
 
@@ -135,66 +131,49 @@ async fn list_backups(ctx: &CliContext) -> CliResult<()> {
             created_at: "2024-01-16".into(),
         },
     ];
-    println!("{}", ctx.output_format.format(&backups));
+    ctx.list(&backups, "No results found.");
     Ok(())
 }
 
 async fn restore_backup(ctx: &CliContext, backup: &str, yes: bool) -> CliResult<()> {
     if !yes {
-        println!("This will overwrite existing data. Run with --yes to confirm.");
+        ctx.warning("This will overwrite existing data. Run with --yes to confirm.");
         return Ok(());
     }
+    ctx.confirm_remote(&format!("restore backup '{}' over the site's data", backup))?;
     print_header("Restoring Backup");
     let spinner = ProgressBar::spinner("Restoring...");
     tokio::time::sleep(std::time::Duration::from_secs(2)).await;
     spinner.finish_and_clear();
-    println!(
-        "{}",
-        ctx.output_format
-            .success(&format!("Restored from {}", backup))
-    );
+    ctx.success(&format!("Restored from {}", backup));
     Ok(())
 }
 
 async fn delete_backup(ctx: &CliContext, backup: &str) -> CliResult<()> {
-    println!(
-        "{}",
-        ctx.output_format
-            .success(&format!("Deleted backup: {}", backup))
-    );
+    ctx.confirm_remote(&format!("delete backup '{}'", backup))?;
+    ctx.success(&format!("Deleted backup: {}", backup));
     Ok(())
 }
 
 async fn download_backup(ctx: &CliContext, backup: &str, output: Option<String>) -> CliResult<()> {
     let output_file = output.unwrap_or_else(|| format!("{}.sql", backup));
-    println!(
-        "{}",
-        ctx.output_format
-            .success(&format!("Downloaded to {}", output_file))
-    );
+    ctx.success(&format!("Downloaded to {}", output_file));
     Ok(())
 }
 
 async fn list_schedules(ctx: &CliContext) -> CliResult<()> {
     print_header("Backup Schedules");
-    println!("{}", ctx.output_format.info("No schedules configured"));
+    ctx.info("No schedules configured");
     Ok(())
 }
 
 async fn create_schedule(ctx: &CliContext, cron: &str, backup_type: &str) -> CliResult<()> {
-    println!(
-        "{}",
-        ctx.output_format
-            .success(&format!("Created schedule: {} ({})", cron, backup_type))
-    );
+    ctx.success(&format!("Created schedule: {} ({})", cron, backup_type));
     Ok(())
 }
 
 async fn delete_schedule(ctx: &CliContext, id: &str) -> CliResult<()> {
-    println!(
-        "{}",
-        ctx.output_format
-            .success(&format!("Deleted schedule: {}", id))
-    );
+    ctx.confirm_remote(&format!("delete backup schedule '{}'", id))?;
+    ctx.success(&format!("Deleted schedule: {}", id));
     Ok(())
 }
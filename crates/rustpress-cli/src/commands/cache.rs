@@ -6,7 +6,7 @@ use tabled::Tabled;
 
 use crate::context::CliContext;
 use crate::error::CliResult;
-use crate::output::{print_header, print_kv, OutputFormatter, ProgressBar};
+use crate::output::{human, print_header, print_kv, ProgressBar};
 
 #[derive(Args, Debug)]
 pub struct CacheCommand {
@@ -92,8 +92,8 @@ async fn show_stats(ctx: &CliContext) -> CliResult<()> {
         },
     ];
 
-    println!("{}", ctx.output_format.format(&stats));
-    println!();
+    ctx.list(&stats, "No results found.");
+    human!();
     print_kv("Total Entries", "2586");
     print_kv("Total Memory", "22.8 MB");
     print_kv("Overall Hit Rate", "91.2%");
@@ -102,6 +102,7 @@ async fn show_stats(ctx: &CliContext) -> CliResult<()> {
 }
 
 async fn clear_cache(ctx: &CliContext, cache_type: &str) -> CliResult<()> {
+    ctx.confirm_remote(&format!("clear the {} cache", cache_type))?;
     print_header(&format!(
         "Clearing {} Cache",
         if cache_type == "all" {
@@ -115,10 +116,7 @@ async fn clear_cache(ctx: &CliContext, cache_type: &str) -> CliResult<()> {
     tokio::time::sleep(std::time::Duration::from_millis(500)).await;
     spinner.finish_and_clear();
 
-    println!(
-        "{}",
-        ctx.output_format.success("Cache cleared successfully")
-    );
+    ctx.success("Cache cleared successfully");
     Ok(())
 }
 
@@ -126,14 +124,14 @@ async fn warm_cache(ctx: &CliContext, pages: bool, posts: bool) -> CliResult<()>
     print_header("Warming Cache");
 
     if !pages && !posts {
-        println!("{}", ctx.output_format.info("Warming all content..."));
+        ctx.info("Warming all content...");
     }
 
     let spinner = ProgressBar::spinner("Warming cache...");
     tokio::time::sleep(std::time::Duration::from_secs(1)).await;
     spinner.finish_and_clear();
 
-    println!("{}", ctx.output_format.success("Cache warmed successfully"));
+    ctx.success("Cache warmed successfully");
     Ok(())
 }
 
@@ -143,18 +141,11 @@ async fn config_cache(ctx: &CliContext, get: Option<String>, set: Option<String>
         match key.as_str() {
             "ttl" => print_kv("ttl", "3600"),
             "max_size" => print_kv("max_size", "100MB"),
-            _ => println!(
-                "{}",
-                ctx.output_format.error(&format!("Unknown key: {}", key))
-            ),
+            _ => ctx.error(&format!("Unknown key: {}", key)),
         }
     } else if let Some(kv) = set {
         if let Some((key, value)) = kv.split_once('=') {
-            println!(
-                "{}",
-                ctx.output_format
-                    .success(&format!("Set {} = {}", key, value))
-            );
+            ctx.success(&format!("Set {} = {}", key, value));
         }
     } else {
         print_header("Cache Configuration");
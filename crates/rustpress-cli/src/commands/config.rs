@@ -1,11 +1,16 @@
 //! Configuration management commands
 
+use std::path::{Path, PathBuf};
+
 use clap::{Args, Subcommand};
 use colored::Colorize;
+use rustpress_core::config_bundle::{
+    self, ConfigBundle, EnvKey, KeyProvider, BUNDLE_KEY_ENV, BUNDLE_KMS_KEY_ENV, BUNDLE_PATH_ENV,
+};
 
 use crate::context::CliContext;
-use crate::error::CliResult;
-use crate::output::{print_header, print_kv, OutputFormatter};
+use crate::error::{CliError, CliResult};
+use crate::output::{human, print_header, print_kv, print_section, OutputFormatter};
 
 #[derive(Args, Debug)]
 pub struct ConfigCommand {
@@ -31,6 +36,59 @@ pub enum ConfigSubcommand {
     },
     /// Show required environment variables
     Env,
+    /// Manage encrypted configuration bundles
+    Bundle {
+        #[command(subcommand)]
+        command: BundleSubcommand,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum BundleSubcommand {
+    /// Generate a bundle key for RUSTPRESS_BUNDLE_KEY
+    Keygen,
+    /// Encrypt a TOML configuration file into a bundle
+    Create {
+        /// Plain TOML configuration to encrypt
+        input: PathBuf,
+        /// Bundle file to write
+        #[arg(default_value = "rustpress.bundle.toml")]
+        bundle: PathBuf,
+        /// Also encrypt for the key in this environment variable
+        #[arg(long = "key-env", value_name = "VAR")]
+        key_env: Vec<String>,
+    },
+    /// Decrypt a bundle into $EDITOR and encrypt the result again
+    Edit {
+        /// Bundle file to edit
+        bundle: PathBuf,
+    },
+    /// Print the decrypted configuration
+    Decrypt {
+        /// Bundle file to decrypt
+        bundle: PathBuf,
+    },
+    /// Show a bundle's recipients and settings without decrypting it
+    Show {
+        /// Bundle file to inspect
+        bundle: PathBuf,
+    },
+    /// Encrypt a bundle's key for the key in another environment variable
+    AddRecipient {
+        /// Bundle file to change
+        bundle: PathBuf,
+        /// Environment variable holding the new recipient's key
+        #[arg(long = "key-env", value_name = "VAR")]
+        key_env: String,
+    },
+    /// Stop encrypting a bundle for a recipient and re-encrypt it under a
+    /// new key for the others, whose keys must all be available
+    RemoveRecipient {
+        /// Bundle file to change
+        bundle: PathBuf,
+        /// Recipient as listed by `config bundle show`
+        recipient: String,
+    },
 }
 
 pub async fn execute(ctx: &CliContext, cmd: ConfigCommand) -> CliResult<()> {
@@ -39,13 +97,14 @@ pub async fn execute(ctx: &CliContext, cmd: ConfigCommand) -> CliResult<()> {
         ConfigSubcommand::Validate => validate_config(ctx),
         ConfigSubcommand::Init { output } => init_config(ctx, &output),
         ConfigSubcommand::Env => show_env(ctx),
+        ConfigSubcommand::Bundle { command } => bundle(ctx, command).await,
     }
 }
 
 fn show_config(ctx: &CliContext, secrets: bool) -> CliResult<()> {
     print_header("Current Configuration");
 
-    println!("{}", "Server".bold());
+    print_section("Server");
     print_kv(
         "  Host",
         &std::env::var("RUSTPRESS_HOST").unwrap_or_else(|_| "127.0.0.1".into()),
@@ -55,8 +114,8 @@ fn show_config(ctx: &CliContext, secrets: bool) -> CliResult<()> {
         &std::env::var("RUSTPRESS_PORT").unwrap_or_else(|_| "3080".into()),
     );
 
-    println!();
-    println!("{}", "Database".bold());
+    human!();
+    print_section("Database");
     let db_url = std::env::var("DATABASE_URL").unwrap_or_else(|_| "(not set)".into());
     if secrets {
         print_kv("  URL", &db_url);
@@ -70,8 +129,8 @@ fn show_config(ctx: &CliContext, secrets: bool) -> CliResult<()> {
         print_kv("  URL", &masked);
     }
 
-    println!();
-    println!("{}", "Storage".bold());
+    human!();
+    print_section("Storage");
     print_kv(
         "  Path",
         &std::env::var("STORAGE_PATH").unwrap_or_else(|_| "./storage".into()),
@@ -81,8 +140,8 @@ fn show_config(ctx: &CliContext, secrets: bool) -> CliResult<()> {
         &std::env::var("THEMES_PATH").unwrap_or_else(|_| "./themes".into()),
     );
 
-    println!();
-    println!("{}", "Auth".bold());
+    human!();
+    print_section("Auth");
     if secrets {
         print_kv(
             "  JWT Secret",
@@ -119,17 +178,17 @@ fn validate_config(ctx: &CliContext) -> CliResult<()> {
     }
 
     if errors.is_empty() && warnings.is_empty() {
-        println!("{}", ctx.output_format.success("Configuration is valid"));
+        ctx.success("Configuration is valid");
     } else {
         for error in &errors {
-            println!("{}", ctx.output_format.error(error));
+            ctx.error(error);
         }
         for warning in &warnings {
-            println!("{}", ctx.output_format.warning(warning));
+            ctx.warning(warning);
         }
         if !errors.is_empty() {
-            println!();
-            println!("{}", "Configuration has errors".red());
+            human!();
+            human!("{}", "Configuration has errors".red());
         }
     }
 
@@ -164,9 +223,9 @@ CACHE_MAX_CAPACITY=10000
 "#;
 
     std::fs::write(output, template)?;
-    println!("Created configuration file: {}", output.green());
-    println!();
-    println!("Edit the file and set your database connection URL.");
+    human!("Created configuration file: {}", output.green());
+    human!();
+    human!("Edit the file and set your database connection URL.");
 
     Ok(())
 }
@@ -174,11 +233,11 @@ CACHE_MAX_CAPACITY=10000
 fn show_env(_ctx: &CliContext) -> CliResult<()> {
     print_header("Required Environment Variables");
 
-    println!("{}", "Required:".bold());
+    print_section("Required:");
     print_kv("  DATABASE_URL", "PostgreSQL connection URL");
 
-    println!();
-    println!("{}", "Optional:".bold());
+    human!();
+    print_section("Optional:");
     print_kv("  RUSTPRESS_HOST", "Server bind host (default: 127.0.0.1)");
     print_kv("  RUSTPRESS_PORT", "Server bind port (default: 3080)");
     print_kv("  JWT_SECRET", "JWT signing secret");
@@ -190,5 +249,265 @@ fn show_env(_ctx: &CliContext) -> CliResult<()> {
         "Maximum cache entries (default: 10000)",
     );
 
+    human!();
+    print_section("Encrypted configuration bundle:");
+    print_kv(
+        &format!("  {}", BUNDLE_PATH_ENV),
+        "Bundle file to load at startup",
+    );
+    print_kv(
+        &format!("  {}", BUNDLE_KEY_ENV),
+        "Base64 key the bundle is encrypted for",
+    );
+    print_kv(
+        &format!("  {}", BUNDLE_KMS_KEY_ENV),
+        "Vault transit key the bundle is encrypted for (with VAULT_ADDR and VAULT_TOKEN)",
+    );
+
+    Ok(())
+}
+
+async fn bundle(ctx: &CliContext, command: BundleSubcommand) -> CliResult<()> {
+    match command {
+        BundleSubcommand::Keygen => {
+            ctx.text(&EnvKey::generate());
+            Ok(())
+        }
+        BundleSubcommand::Create {
+            input,
+            bundle,
+            key_env,
+        } => create_bundle(ctx, &input, &bundle, &key_env).await,
+        BundleSubcommand::Edit { bundle } => edit_bundle(ctx, &bundle).await,
+        BundleSubcommand::Decrypt { bundle } => decrypt_bundle(ctx, &bundle).await,
+        BundleSubcommand::Show { bundle } => show_bundle(ctx, &bundle),
+        BundleSubcommand::AddRecipient { bundle, key_env } => {
+            add_bundle_recipient(ctx, &bundle, &key_env).await
+        }
+        BundleSubcommand::RemoveRecipient { bundle, recipient } => {
+            remove_bundle_recipient(ctx, &bundle, &recipient).await
+        }
+    }
+}
+
+/// Keys from the environment, plus those in `key_env`
+fn bundle_keys(key_env: &[String]) -> CliResult<Vec<Box<dyn KeyProvider>>> {
+    let mut providers = config_bundle::env_providers().map_err(bundle_error)?;
+    for var in key_env {
+        providers.push(Box::new(EnvKey::from_env(var).map_err(bundle_error)?));
+    }
+    if providers.is_empty() {
+        return Err(CliError::Config(format!(
+            "No bundle key: set {} (see `config bundle keygen`) or VAULT_ADDR, VAULT_TOKEN and {}",
+            BUNDLE_KEY_ENV, BUNDLE_KMS_KEY_ENV
+        )));
+    }
+    Ok(providers)
+}
+
+fn bundle_error(e: rustpress_core::Error) -> CliError {
+    match e {
+        rustpress_core::Error::Configuration { message } => CliError::Config(message),
+        other => CliError::Config(other.to_string()),
+    }
+}
+
+fn read_bundle(path: &Path) -> CliResult<ConfigBundle> {
+    ConfigBundle::read(path).map_err(bundle_error)
+}
+
+fn write_bundle(path: &Path, bundle: &ConfigBundle) -> CliResult<()> {
+    std::fs::write(path, bundle.to_toml_string().map_err(bundle_error)?)?;
+    Ok(())
+}
+
+fn parse_toml(text: &str, source: &Path) -> CliResult<toml::Table> {
+    text.parse().map_err(|e| {
+        CliError::InvalidInput(format!("{} is not valid TOML: {}", source.display(), e))
+    })
+}
+
+async fn create_bundle(
+    ctx: &CliContext,
+    input: &Path,
+    output: &Path,
+    key_env: &[String],
+) -> CliResult<()> {
+    let config = parse_toml(&std::fs::read_to_string(input)?, input)?;
+    let providers = bundle_keys(key_env)?;
+    let providers: Vec<&dyn KeyProvider> = providers.iter().map(|p| p.as_ref()).collect();
+
+    let bundle = ConfigBundle::seal(&config, &providers)
+        .await
+        .map_err(bundle_error)?;
+    write_bundle(output, &bundle)?;
+
+    ctx.success(&format!(
+        "Encrypted {} into {} for {}",
+        input.display(),
+        output.display(),
+        bundle.recipients().join(", ")
+    ));
+    ctx.warning(&format!(
+        "{} still holds the configuration in plain text; delete it once the bundle is in place",
+        input.display()
+    ));
+    Ok(())
+}
+
+async fn edit_bundle(ctx: &CliContext, path: &Path) -> CliResult<()> {
+    let mut bundle = read_bundle(path)?;
+    let providers = bundle_keys(&[])?;
+    let providers: Vec<&dyn KeyProvider> = providers.iter().map(|p| p.as_ref()).collect();
+    let key = bundle.unlock(&providers).await.map_err(bundle_error)?;
+    let config = bundle.decrypt(&key).map_err(bundle_error)?;
+    let original =
+        toml::to_string_pretty(&config).map_err(|e| CliError::Serialization(e.to_string()))?;
+
+    // Decrypted values only ever sit in a file only the user can read,
+    // removed as soon as the editor exits
+    let scratch =
+        std::env::temp_dir().join(format!("rustpress-bundle-{}.toml", uuid::Uuid::new_v4()));
+    write_private(&scratch, &original)?;
+    let edited = run_editor(&scratch).and_then(|()| Ok(std::fs::read_to_string(&scratch)?));
+    let _ = std::fs::remove_file(&scratch);
+    let edited = edited?;
+
+    if edited == original {
+        ctx.info("No changes");
+        return Ok(());
+    }
+    bundle
+        .update(&key, &parse_toml(&edited, path)?)
+        .map_err(bundle_error)?;
+    write_bundle(path, &bundle)?;
+    ctx.success(&format!("Updated {}", path.display()));
+    Ok(())
+}
+
+fn write_private(path: &Path, contents: &str) -> CliResult<()> {
+    use std::io::Write;
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(contents.as_bytes())?;
+    Ok(())
+}
+
+fn run_editor(path: &Path) -> CliResult<()> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    // Editors are often configured with flags, e.g. `code --wait`
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or("vi");
+    let status = std::process::Command::new(program)
+        .args(words)
+        .arg(path)
+        .status()
+        .map_err(|e| CliError::OperationFailed(format!("Failed to start {}: {}", editor, e)))?;
+    if !status.success() {
+        return Err(CliError::OperationFailed(format!(
+            "{} exited with {}; the bundle was left unchanged",
+            editor, status
+        )));
+    }
+    Ok(())
+}
+
+async fn decrypt_bundle(ctx: &CliContext, path: &Path) -> CliResult<()> {
+    let bundle = read_bundle(path)?;
+    let providers = bundle_keys(&[])?;
+    let providers: Vec<&dyn KeyProvider> = providers.iter().map(|p| p.as_ref()).collect();
+    let config = bundle.open(&providers).await.map_err(bundle_error)?;
+
+    ctx.text(&toml::to_string_pretty(&config).map_err(|e| CliError::Serialization(e.to_string()))?);
+    Ok(())
+}
+
+fn show_bundle(ctx: &CliContext, path: &Path) -> CliResult<()> {
+    let bundle = read_bundle(path)?;
+    let settings = bundle.keys();
+
+    if ctx.output_format.is_machine_readable() {
+        ctx.item(&serde_json::json!({
+            "recipients": bundle.recipients(),
+            "updated_at": bundle.updated_at(),
+            "settings": settings,
+        }));
+        return Ok(());
+    }
+
+    print_header("Configuration Bundle");
+    print_kv("File", &path.display().to_string());
+    print_kv("Updated", bundle.updated_at());
+    human!();
+    print_section("Recipients");
+    for recipient in bundle.recipients() {
+        human!("  {}", recipient);
+    }
+    human!();
+    print_section("Settings");
+    for setting in settings {
+        human!("  {}", setting);
+    }
+    Ok(())
+}
+
+async fn add_bundle_recipient(ctx: &CliContext, path: &Path, key_env: &str) -> CliResult<()> {
+    let mut bundle = read_bundle(path)?;
+    let providers = bundle_keys(&[])?;
+    let providers: Vec<&dyn KeyProvider> = providers.iter().map(|p| p.as_ref()).collect();
+    let key = bundle.unlock(&providers).await.map_err(bundle_error)?;
+
+    let recipient = EnvKey::from_env(key_env).map_err(bundle_error)?;
+    bundle
+        .add_recipient(&key, &recipient)
+        .await
+        .map_err(bundle_error)?;
+    write_bundle(path, &bundle)?;
+    ctx.success(&format!(
+        "{} can now be opened with {}",
+        path.display(),
+        recipient.recipient()
+    ));
+    Ok(())
+}
+
+async fn remove_bundle_recipient(ctx: &CliContext, path: &Path, recipient: &str) -> CliResult<()> {
+    let mut bundle = read_bundle(path)?;
+    if !bundle.remove_recipient(recipient).map_err(bundle_error)? {
+        return Err(CliError::NotFound(format!(
+            "{} is not a recipient of {}",
+            recipient,
+            path.display()
+        )));
+    }
+
+    // The removed recipient may have kept the data key, so the bundle is
+    // re-encrypted under a new one, wrapped for the remaining recipients
+    // with the keys in their environment variables
+    let vars: Vec<String> = bundle
+        .recipients()
+        .into_iter()
+        .filter_map(|r| r.strip_prefix("env:"))
+        .filter(|var| std::env::var_os(var).is_some())
+        .map(str::to_string)
+        .collect();
+    let providers = bundle_keys(&vars)?;
+    let providers: Vec<&dyn KeyProvider> = providers.iter().map(|p| p.as_ref()).collect();
+    bundle.rotate(&providers).await.map_err(bundle_error)?;
+
+    write_bundle(path, &bundle)?;
+    ctx.success(&format!(
+        "Removed {} from {} and re-encrypted it with a new key",
+        recipient,
+        path.display()
+    ));
     Ok(())
 }
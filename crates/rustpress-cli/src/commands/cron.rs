@@ -6,7 +6,7 @@ use tabled::Tabled;
 
 use crate::context::CliContext;
 use crate::error::{CliError, CliResult};
-use crate::output::{print_header, print_kv, OutputFormatter, ProgressBar};
+use crate::output::{human, print_header, print_kv, ProgressBar};
 
 #[derive(Args, Debug)]
 pub struct CronCommand {
@@ -151,12 +151,8 @@ async fn list_tasks(ctx: &CliContext) -> CliResult<()> {
         let status = response.status();
         if status == reqwest::StatusCode::NOT_FOUND {
             // Fallback: Show built-in tasks info
-            println!(
-                "{}",
-                ctx.output_format
-                    .info("Cron API not available. Showing default tasks:")
-            );
-            println!();
+            ctx.info("Cron API not available. Showing default tasks:");
+            human!();
 
             let default_tasks = vec![
                 CronTaskRow {
@@ -185,7 +181,7 @@ async fn list_tasks(ctx: &CliContext) -> CliResult<()> {
                 },
             ];
 
-            println!("{}", ctx.output_format.format(&default_tasks));
+            ctx.list(&default_tasks, "No results found.");
             return Ok(());
         }
         let body = response.text().await.unwrap_or_default();
@@ -201,11 +197,11 @@ async fn list_tasks(ctx: &CliContext) -> CliResult<()> {
         .map_err(|e| CliError::Serialization(format!("Failed to parse response: {}", e)))?;
 
     if tasks.is_empty() {
-        println!("{}", ctx.output_format.info("No scheduled tasks found"));
+        ctx.info("No scheduled tasks found");
     } else {
-        println!("{}", ctx.output_format.format(&tasks));
-        println!();
-        println!("Total: {} task(s)", tasks.len());
+        ctx.list(&tasks, "No results found.");
+        human!();
+        human!("Total: {} task(s)", tasks.len());
     }
 
     Ok(())
@@ -288,11 +284,7 @@ async fn run_task(ctx: &CliContext, task: &str) -> CliResult<()> {
     if !response.status().is_success() {
         let status = response.status();
         if status == reqwest::StatusCode::NOT_FOUND {
-            println!(
-                "{}",
-                ctx.output_format
-                    .info("Cron task execution via API is not available.")
-            );
+            ctx.info("Cron task execution via API is not available.");
             return Ok(());
         }
         let body = response.text().await.unwrap_or_default();
@@ -302,11 +294,7 @@ async fn run_task(ctx: &CliContext, task: &str) -> CliResult<()> {
         )));
     }
 
-    println!(
-        "{}",
-        ctx.output_format
-            .success(&format!("Task '{}' executed successfully", task))
-    );
+    ctx.success(&format!("Task '{}' executed successfully", task));
     Ok(())
 }
 
@@ -330,11 +318,7 @@ async fn enable_task(ctx: &CliContext, task: &str) -> CliResult<()> {
         )));
     }
 
-    println!(
-        "{}",
-        ctx.output_format
-            .success(&format!("Task '{}' enabled", task))
-    );
+    ctx.success(&format!("Task '{}' enabled", task));
     Ok(())
 }
 
@@ -358,11 +342,7 @@ async fn disable_task(ctx: &CliContext, task: &str) -> CliResult<()> {
         )));
     }
 
-    println!(
-        "{}",
-        ctx.output_format
-            .success(&format!("Task '{}' disabled", task))
-    );
+    ctx.success(&format!("Task '{}' disabled", task));
     Ok(())
 }
 
@@ -390,10 +370,7 @@ async fn show_history(ctx: &CliContext, task: Option<String>, limit: u32) -> Cli
     if !response.status().is_success() {
         let status = response.status();
         if status == reqwest::StatusCode::NOT_FOUND {
-            println!(
-                "{}",
-                ctx.output_format.info("No execution history available")
-            );
+            ctx.info("No execution history available");
             return Ok(());
         }
         let body = response.text().await.unwrap_or_default();
@@ -408,11 +385,7 @@ async fn show_history(ctx: &CliContext, task: Option<String>, limit: u32) -> Cli
         .await
         .map_err(|e| CliError::Serialization(format!("Failed to parse response: {}", e)))?;
 
-    if history.is_empty() {
-        println!("{}", ctx.output_format.info("No execution history found"));
-    } else {
-        println!("{}", ctx.output_format.format(&history));
-    }
+    ctx.list(&history, "No execution history found");
 
     Ok(())
 }
@@ -453,11 +426,7 @@ async fn create_task(
     if !response.status().is_success() {
         let status = response.status();
         if status == reqwest::StatusCode::NOT_FOUND {
-            println!(
-                "{}",
-                ctx.output_format
-                    .info("Cron task creation via API is not available.")
-            );
+            ctx.info("Cron task creation via API is not available.");
             return Ok(());
         }
         let body = response.text().await.unwrap_or_default();
@@ -467,11 +436,7 @@ async fn create_task(
         )));
     }
 
-    println!(
-        "{}",
-        ctx.output_format
-            .success(&format!("Task '{}' created successfully", name))
-    );
+    ctx.success(&format!("Task '{}' created successfully", name));
     Ok(())
 }
 
@@ -485,9 +450,11 @@ async fn delete_task(ctx: &CliContext, task: &str, force: bool) -> CliResult<()>
             .map_err(|e| CliError::InvalidInput(format!("Failed to get confirmation: {}", e)))?;
 
         if !confirmed {
-            println!("Operation cancelled.");
+            ctx.info("Operation cancelled.");
             return Ok(());
         }
+    } else {
+        ctx.confirm_remote(&format!("delete task '{}'", task))?;
     }
 
     let client = ctx.http_client();
@@ -509,10 +476,6 @@ async fn delete_task(ctx: &CliContext, task: &str, force: bool) -> CliResult<()>
         )));
     }
 
-    println!(
-        "{}",
-        ctx.output_format
-            .success(&format!("Task '{}' deleted", task))
-    );
+    ctx.success(&format!("Task '{}' deleted", task));
     Ok(())
 }
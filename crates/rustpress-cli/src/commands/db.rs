@@ -7,7 +7,7 @@ use tabled::Tabled;
 
 use crate::context::CliContext;
 use crate::error::{CliError, CliResult};
-use crate::output::{print_header, print_kv, OutputFormatter, ProgressBar};
+use crate::output::{human, print_header, print_kv, OutputFormatter, ProgressBar};
 
 #[derive(Args, Debug)]
 pub struct DbCommand {
@@ -252,34 +252,25 @@ async fn run_migrate(
             .map_err(|e| CliError::Network(format!("Failed to fetch migrations: {}", e)))?;
 
         if !response.status().is_success() {
-            println!(
-                "{}",
-                ctx.output_format
-                    .info("Migration status is not available via API. Use direct database access.")
-            );
+            ctx.info("Migration status is not available via API. Use direct database access.");
             return Ok(());
         }
 
         let migrations: Vec<MigrationStatus> = response.json().await.unwrap_or_default();
 
-        if migrations.is_empty() {
-            println!("{}", ctx.output_format.info("No migrations found"));
-        } else {
-            println!("{}", ctx.output_format.format(&migrations));
-        }
+        ctx.list(&migrations, "No migrations found");
 
         return Ok(());
     }
 
     if let Some(n) = rollback {
+        if !dry_run {
+            ctx.confirm_remote(&format!("roll back {} migration(s)", n))?;
+        }
         print_header(&format!("Rolling back {} migration(s)", n));
 
         if dry_run {
-            println!(
-                "{}",
-                ctx.output_format
-                    .warning("Dry run - no changes will be made")
-            );
+            ctx.warning("Dry run - no changes will be made");
         }
 
         let url = format!(
@@ -296,27 +287,18 @@ async fn run_migrate(
             .map_err(|e| CliError::Network(format!("Failed to rollback migrations: {}", e)))?;
 
         if !response.status().is_success() {
-            println!(
-                "{}",
-                ctx.output_format.info(
-                    "Migration rollback is not available via API. Use direct database access."
-                )
-            );
+            ctx.info("Migration rollback is not available via API. Use direct database access.");
             return Ok(());
         }
 
         if !dry_run {
-            println!("{}", ctx.output_format.success("Rollback completed"));
+            ctx.success("Rollback completed");
         }
     } else {
         print_header("Running Migrations");
 
         if dry_run {
-            println!(
-                "{}",
-                ctx.output_format
-                    .warning("Dry run - no changes will be made")
-            );
+            ctx.warning("Dry run - no changes will be made");
         }
 
         let spinner = ProgressBar::spinner("Running migrations...");
@@ -336,11 +318,7 @@ async fn run_migrate(
         spinner.finish_and_clear();
 
         if !response.status().is_success() {
-            println!(
-                "{}",
-                ctx.output_format
-                    .info("Migrations are not available via API. Use direct database access.")
-            );
+            ctx.info("Migrations are not available via API. Use direct database access.");
             return Ok(());
         }
 
@@ -348,13 +326,9 @@ async fn run_migrate(
         let applied = result.get("applied").and_then(|v| v.as_i64()).unwrap_or(0);
 
         if applied == 0 {
-            println!("{}", ctx.output_format.success("Database is up to date"));
+            ctx.success("Database is up to date");
         } else if !dry_run {
-            println!(
-                "{}",
-                ctx.output_format
-                    .success(&format!("Applied {} migration(s)", applied))
-            );
+            ctx.success(&format!("Applied {} migration(s)", applied));
         }
     }
 
@@ -383,17 +357,13 @@ async fn show_status(ctx: &CliContext) -> CliResult<()> {
             Ok(r) if r.status().is_success() => {
                 print_kv("Server", &ctx.server_url());
                 print_kv("Status", "Connected");
-                println!();
-                println!(
-                    "{}",
-                    ctx.output_format.success("Database connection healthy")
-                );
+                human!();
+                ctx.success("Database connection healthy");
             }
             _ => {
                 print_kv("Server", &ctx.server_url());
                 print_kv("Status", "Unable to connect");
-                println!();
-                println!("{}", ctx.output_format.error("Could not connect to server"));
+                return Err(CliError::Network("Could not connect to server".to_string()));
             }
         }
         return Ok(());
@@ -417,11 +387,8 @@ async fn show_status(ctx: &CliContext) -> CliResult<()> {
         print_kv("Tables", &tables.to_string());
     }
 
-    println!();
-    println!(
-        "{}",
-        ctx.output_format.success("Database connection healthy")
-    );
+    human!();
+    ctx.success("Database connection healthy");
 
     Ok(())
 }
@@ -467,25 +434,17 @@ async fn create_backup(
             .await
             .map_err(|e| CliError::Network(format!("Failed to download backup: {}", e)))?;
         std::fs::write(&output_file, &bytes)?;
-        println!(
-            "{}",
-            ctx.output_format
-                .success(&format!("Backup created: {}", output_file))
-        );
+        ctx.success(&format!("Backup created: {}", output_file));
     } else {
-        println!();
-        println!(
-            "{}",
-            ctx.output_format
-                .info("Backup via API not available. To create a backup, use pg_dump:")
-        );
-        println!();
-        println!("  {} pg_dump $DATABASE_URL > {}", "$".dimmed(), output_file);
+        human!();
+        ctx.info("Backup via API not available. To create a backup, use pg_dump:");
+        human!();
+        human!("  {} pg_dump $DATABASE_URL > {}", "$".dimmed(), output_file);
 
         if include_media {
-            println!();
-            println!("  {} # Also backup media files:", "#".dimmed());
-            println!(
+            human!();
+            human!("  {} # Also backup media files:", "#".dimmed());
+            human!(
                 "  {} tar -czf rustpress_media_{}.tar.gz ./storage/media",
                 "$".dimmed(),
                 timestamp
@@ -502,23 +461,20 @@ async fn restore_backup(ctx: &CliContext, file: &str, yes: bool) -> CliResult<()
     print_kv("File", file);
 
     if !yes {
-        println!();
-        println!(
+        human!();
+        human!(
             "{}",
             "WARNING: This will overwrite existing data!".red().bold()
         );
-        println!();
-        println!("To confirm, run with --yes flag");
+        human!();
+        human!("To confirm, run with --yes flag");
         return Ok(());
     }
 
-    println!();
-    println!(
-        "{}",
-        ctx.output_format.info("To restore a backup, use psql:")
-    );
-    println!();
-    println!("  {} psql $DATABASE_URL < {}", "$".dimmed(), file);
+    human!();
+    ctx.info("To restore a backup, use psql:");
+    human!();
+    human!("  {} psql $DATABASE_URL < {}", "$".dimmed(), file);
 
     Ok(())
 }
@@ -557,11 +513,7 @@ async fn run_query(ctx: &CliContext, sql: &str, explain: bool, limit: u32) -> Cl
         .map_err(|e| CliError::Network(format!("Failed to execute query: {}", e)))?;
 
     if !response.status().is_success() {
-        println!(
-            "{}",
-            ctx.output_format
-                .error("Query execution via API is not available. Use direct database access.")
-        );
+        ctx.error("Query execution via API is not available. Use direct database access.");
         return Ok(());
     }
 
@@ -569,11 +521,15 @@ async fn run_query(ctx: &CliContext, sql: &str, explain: bool, limit: u32) -> Cl
 
     if let Some(rows) = result.get("rows").and_then(|v| v.as_array()) {
         if rows.is_empty() {
-            println!("{}", ctx.output_format.info("No results"));
+            ctx.info("No results");
         } else {
-            println!("{}", serde_json::to_string_pretty(&rows)?);
-            println!();
-            println!("{} row(s) returned", rows.len());
+            if ctx.output_format.is_machine_readable() {
+                ctx.item(rows);
+            } else {
+                println!("{}", serde_json::to_string_pretty(&rows)?);
+                human!();
+                human!("{} row(s) returned", rows.len());
+            }
         }
     }
 
@@ -594,24 +550,20 @@ async fn list_tables(ctx: &CliContext, verbose: bool) -> CliResult<()> {
         .map_err(|e| CliError::Network(format!("Failed to fetch tables: {}", e)))?;
 
     if !response.status().is_success() {
-        println!(
-            "{}",
-            ctx.output_format
-                .info("Table listing via API is not available. Use direct database access.")
-        );
+        ctx.info("Table listing via API is not available. Use direct database access.");
         return Ok(());
     }
 
     let tables: Vec<TableInfo> = response.json().await.unwrap_or_default();
 
     if tables.is_empty() {
-        println!("{}", ctx.output_format.info("No tables found"));
+        ctx.info("No tables found");
     } else {
-        println!("{}", ctx.output_format.format(&tables));
+        ctx.list(&tables, "No results found.");
 
         if verbose {
-            println!();
-            println!("Total tables: {}", tables.len());
+            human!();
+            human!("Total tables: {}", tables.len());
         }
     }
 
@@ -665,22 +617,14 @@ async fn export_table(
     spinner.finish_and_clear();
 
     if !response.status().is_success() {
-        println!(
-            "{}",
-            ctx.output_format
-                .info("Table export via API is not available. Use direct database access.")
-        );
+        ctx.info("Table export via API is not available. Use direct database access.");
         return Ok(());
     }
 
     let content = response.text().await.unwrap_or_default();
     std::fs::write(&output_file, content)?;
 
-    println!(
-        "{}",
-        ctx.output_format
-            .success(&format!("Exported to {}", output_file))
-    );
+    ctx.success(&format!("Exported to {}", output_file));
 
     Ok(())
 }
@@ -711,8 +655,8 @@ async fn import_data(
     }
 
     if dry_run {
-        println!();
-        println!("{}", ctx.output_format.warning("Dry run - validating only"));
+        human!();
+        ctx.warning("Dry run - validating only");
     }
 
     let content = std::fs::read_to_string(file)?;
@@ -727,16 +671,12 @@ async fn import_data(
         _ => 0,
     };
 
-    println!();
-    println!("Found {} record(s) to import", row_count);
+    human!();
+    human!("Found {} record(s) to import", row_count);
 
     if !dry_run {
-        println!();
-        println!(
-            "{}",
-            ctx.output_format
-                .info("Data import via API is not available. Use direct database access or psql.")
-        );
+        human!();
+        ctx.info("Data import via API is not available. Use direct database access or psql.");
     }
 
     Ok(())
@@ -764,12 +704,7 @@ async fn optimize_tables(ctx: &CliContext, table: Option<String>) -> CliResult<(
     spinner.finish_and_clear();
 
     if !response.status().is_success() {
-        println!(
-            "{}",
-            ctx.output_format.info(
-                "Database optimization via API is not available. Use VACUUM ANALYZE directly."
-            )
-        );
+        ctx.info("Database optimization via API is not available. Use VACUUM ANALYZE directly.");
         return Ok(());
     }
 
@@ -779,11 +714,7 @@ async fn optimize_tables(ctx: &CliContext, table: Option<String>) -> CliResult<(
         .and_then(|v| v.as_i64())
         .unwrap_or(1);
 
-    println!(
-        "{}",
-        ctx.output_format
-            .success(&format!("Optimized {} table(s)", count))
-    );
+    ctx.success(&format!("Optimized {} table(s)", count));
 
     Ok(())
 }
@@ -808,13 +739,9 @@ async fn audit_log(
             .map_err(|e| CliError::Network(format!("Failed to clear audit log: {}", e)))?;
 
         if response.status().is_success() {
-            println!("{}", ctx.output_format.success("Audit log cleared"));
+            ctx.success("Audit log cleared");
         } else {
-            println!(
-                "{}",
-                ctx.output_format
-                    .info("Audit log clearing via API is not available.")
-            );
+            ctx.info("Audit log clearing via API is not available.");
         }
         return Ok(());
     }
@@ -834,21 +761,13 @@ async fn audit_log(
         .map_err(|e| CliError::Network(format!("Failed to fetch audit log: {}", e)))?;
 
     if !response.status().is_success() {
-        println!(
-            "{}",
-            ctx.output_format
-                .info("Audit log via API is not available.")
-        );
+        ctx.info("Audit log via API is not available.");
         return Ok(());
     }
 
     let logs: Vec<AuditLogEntry> = response.json().await.unwrap_or_default();
 
-    if logs.is_empty() {
-        println!("{}", ctx.output_format.info("No audit log entries found"));
-    } else {
-        println!("{}", ctx.output_format.format(&logs));
-    }
+    ctx.list(&logs, "No audit log entries found");
 
     Ok(())
 }
@@ -0,0 +1,162 @@
+//! Installation diagnostics commands
+
+use clap::{Args, Subcommand};
+use serde::{Deserialize, Serialize};
+use tabled::Tabled;
+
+use crate::context::CliContext;
+use crate::error::{CliError, CliResult};
+use crate::output::{human, print_header, print_kv, OutputFormatter};
+
+#[derive(Args, Debug)]
+pub struct DoctorCommand {
+    #[command(subcommand)]
+    pub command: DoctorSubcommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum DoctorSubcommand {
+    /// Check installed themes and plugins against this installation
+    Compat,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CompatAudit {
+    pub platform: PlatformInfo,
+    pub compatible: bool,
+    pub plugins: Vec<CompatReport>,
+    pub themes: Vec<CompatReport>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PlatformInfo {
+    pub version: String,
+    pub features: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CompatReport {
+    pub kind: String,
+    pub id: String,
+    pub version: String,
+    pub issues: Vec<CompatIssue>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CompatIssue {
+    pub severity: String,
+    pub kind: String,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize, Tabled)]
+pub struct CompatRow {
+    #[tabled(rename = "Kind")]
+    pub kind: String,
+    #[tabled(rename = "ID")]
+    pub id: String,
+    #[tabled(rename = "Version")]
+    pub version: String,
+    #[tabled(rename = "Status")]
+    pub status: String,
+}
+
+impl CompatReport {
+    fn count(&self, severity: &str) -> usize {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity == severity)
+            .count()
+    }
+
+    fn row(&self) -> CompatRow {
+        let status = match (self.count("error"), self.count("warning")) {
+            (0, 0) => "ok".to_string(),
+            (0, warnings) => format!("{} warning(s)", warnings),
+            (errors, _) => format!("incompatible ({} issue(s))", errors),
+        };
+        CompatRow {
+            kind: self.kind.clone(),
+            id: self.id.clone(),
+            version: self.version.clone(),
+            status,
+        }
+    }
+}
+
+pub async fn execute(ctx: &CliContext, cmd: DoctorCommand) -> CliResult<()> {
+    match cmd.command {
+        DoctorSubcommand::Compat => check_compat(ctx).await,
+    }
+}
+
+async fn check_compat(ctx: &CliContext) -> CliResult<()> {
+    print_header("Compatibility Audit");
+
+    let client = ctx.http_client();
+    let url = format!("{}/api/v1/compat", ctx.server_url());
+
+    let response = client
+        .get(&url)
+        .header("Authorization", ctx.auth_header()?)
+        .send()
+        .await
+        .map_err(|e| CliError::Network(format!("Failed to run compatibility audit: {}", e)))?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(CliError::OperationFailed(format!(
+            "Failed to run compatibility audit ({}): {}",
+            status, body
+        )));
+    }
+
+    let audit: CompatAudit = response
+        .json()
+        .await
+        .map_err(|e| CliError::Serialization(format!("Failed to parse response: {}", e)))?;
+
+    let reports: Vec<&CompatReport> = audit.plugins.iter().chain(&audit.themes).collect();
+    let incompatible = reports.iter().filter(|r| r.count("error") > 0).count();
+
+    if ctx.output_format.is_machine_readable() {
+        ctx.item(&audit);
+    } else {
+        print_kv("RustPress", &audit.platform.version);
+        print_kv(
+            "Features",
+            &if audit.platform.features.is_empty() {
+                "none".to_string()
+            } else {
+                audit.platform.features.join(", ")
+            },
+        );
+        human!();
+
+        let rows: Vec<CompatRow> = reports.iter().map(|r| r.row()).collect();
+        ctx.list(&rows, "No themes or plugins installed");
+
+        for report in &reports {
+            for issue in &report.issues {
+                let msg = format!("{} '{}': {}", report.kind, report.id, issue.message);
+                if issue.severity == "error" {
+                    ctx.error(&msg);
+                } else {
+                    ctx.warning(&msg);
+                }
+            }
+        }
+    }
+
+    if incompatible > 0 {
+        return Err(CliError::PartialFailure(format!(
+            "{} of {} extension(s) are incompatible",
+            incompatible,
+            reports.len()
+        )));
+    }
+    ctx.success("All installed themes and plugins are compatible");
+
+    Ok(())
+}
@@ -5,46 +5,72 @@
 use clap::{Args, Subcommand};
 use colored::Colorize;
 use serde::{Deserialize, Serialize};
-
-use dialoguer::Confirm;
+use std::path::PathBuf;
+use std::time::Duration;
 
 use crate::context::CliContext;
 use crate::error::{CliError, CliResult};
-use crate::output::{print_header, print_kv, ProgressBar};
+use crate::output::{human, print_header, print_kv, print_section, ProgressBar};
 
+/// Import content from another system
 #[derive(Args, Debug)]
-pub struct ImportExportCommand {
+pub struct ImportCommand {
     #[command(subcommand)]
-    pub command: ImportExportSubcommand,
+    pub command: ImportSubcommand,
 }
 
 #[derive(Subcommand, Debug)]
-pub enum ImportExportSubcommand {
-    /// Import from WordPress WXR (XML) file
-    Import {
-        /// Path to the WXR file
-        file: String,
+pub enum ImportSubcommand {
+    /// Import a WordPress export (WXR) file: posts, pages, media,
+    /// categories, tags, comments and authors
+    #[command(alias = "wp")]
+    Wordpress(WordpressImportArgs),
+}
 
-        /// Import posts
-        #[arg(long, default_value = "true")]
-        posts: bool,
+#[derive(Args, Debug)]
+pub struct WordpressImportArgs {
+    /// Path to the WXR file (Tools > Export in WordPress)
+    pub file: PathBuf,
 
-        /// Import pages
-        #[arg(long, default_value = "true")]
-        pages: bool,
+    /// Don't download attachments; content keeps linking to the old site
+    #[arg(long)]
+    pub skip_media: bool,
 
-        /// Import media (download attachments)
-        #[arg(long)]
-        media: bool,
+    /// Don't import comments
+    #[arg(long)]
+    pub skip_comments: bool,
 
-        /// Import users
-        #[arg(long)]
-        users: bool,
+    /// Attribute everything to you instead of matching or creating authors
+    #[arg(long)]
+    pub assign_to_me: bool,
 
-        /// Dry run - don't actually import
-        #[arg(long)]
-        dry_run: bool,
-    },
+    /// Don't redirect old permalinks to the imported content
+    #[arg(long)]
+    pub no_redirects: bool,
+
+    /// Write the old path to new location map to this CSV file
+    #[arg(long, value_name = "FILE")]
+    pub redirect_map: Option<PathBuf>,
+
+    /// Count what would be imported without changing anything
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Return once the import has started instead of waiting for it
+    #[arg(long)]
+    pub no_wait: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct ImportExportCommand {
+    #[command(subcommand)]
+    pub command: ImportExportSubcommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ImportExportSubcommand {
+    /// Import from WordPress WXR (XML) file (same as 'import wordpress')
+    Import(WordpressImportArgs),
 
     /// Export to WordPress WXR (XML) format
     Export {
@@ -93,14 +119,7 @@ struct WxrAnalysis {
 
 pub async fn execute(ctx: &CliContext, cmd: ImportExportCommand) -> CliResult<()> {
     match cmd.command {
-        ImportExportSubcommand::Import {
-            file,
-            posts,
-            pages,
-            media,
-            users,
-            dry_run,
-        } => import_wxr(ctx, &file, posts, pages, media, users, dry_run).await,
+        ImportExportSubcommand::Import(args) => import_wordpress(ctx, args).await,
         ImportExportSubcommand::Export {
             output,
             posts,
@@ -113,144 +132,258 @@ pub async fn execute(ctx: &CliContext, cmd: ImportExportCommand) -> CliResult<()
     }
 }
 
-async fn import_wxr(
-    ctx: &CliContext,
-    file: &str,
-    import_posts: bool,
-    import_pages: bool,
-    import_media: bool,
-    import_users: bool,
-    dry_run: bool,
-) -> CliResult<()> {
-    print_header("WordPress Import");
-
-    // Check file exists
-    let path = std::path::Path::new(file);
-    if !path.exists() {
-        return Err(CliError::NotFound(format!("File not found: {}", file)));
+pub async fn execute_import(ctx: &CliContext, cmd: ImportCommand) -> CliResult<()> {
+    match cmd.command {
+        ImportSubcommand::Wordpress(args) => import_wordpress(ctx, args).await,
     }
+}
 
-    print_kv("File", file);
-    print_kv("Dry Run", if dry_run { "Yes" } else { "No" });
-    println!();
+/// Largest WXR file the server accepts
+const MAX_WXR_SIZE: u64 = 64 * 1024 * 1024;
 
-    // Analyze file first
-    let analysis = analyze_wxr_file(file)?;
+/// How often a running import is polled for progress
+const IMPORT_POLL_INTERVAL: Duration = Duration::from_secs(1);
 
-    println!("{}", "Content found:".cyan());
-    if import_posts {
-        println!("  {} {}", "Posts:".green(), analysis.posts);
-    }
-    if import_pages {
-        println!("  {} {}", "Pages:".green(), analysis.pages);
-    }
-    if import_media {
-        println!("  {} {}", "Attachments:".green(), analysis.attachments);
-    }
-    if import_users {
-        println!("  {} {}", "Users:".green(), analysis.users);
-    }
-    println!("  {} {}", "Categories:".green(), analysis.categories);
-    println!("  {} {}", "Tags:".green(), analysis.tags);
-    println!();
+#[derive(Debug, Deserialize)]
+struct ApiEnvelope<T> {
+    data: T,
+}
 
-    if dry_run {
-        println!("{}", "Dry run complete. No changes were made.".yellow());
-        return Ok(());
-    }
+#[derive(Debug, Serialize, Deserialize)]
+struct ImportItemError {
+    item: i64,
+    title: String,
+    message: String,
+}
 
-    // Confirm import
-    let confirmed = Confirm::new()
-        .with_prompt("Proceed with import?")
-        .default(false)
-        .interact()
-        .map_err(|e| CliError::InvalidInput(format!("Failed to get confirmation: {}", e)))?;
+#[derive(Debug, Serialize, Deserialize)]
+struct WordpressImportRun {
+    id: String,
+    status: String,
+    dry_run: bool,
+    source_url: Option<String>,
+    total: u64,
+    processed: u64,
+    authors: u64,
+    categories: u64,
+    tags: u64,
+    posts: u64,
+    pages: u64,
+    media: u64,
+    comments: u64,
+    redirects: u64,
+    skipped: u64,
+    failed: u64,
+    #[serde(default)]
+    errors: Vec<ImportItemError>,
+}
 
-    if !confirmed {
-        println!("{}", "Import cancelled.".yellow());
-        return Ok(());
+/// Query string of the import endpoint for the chosen options
+fn import_query(args: &WordpressImportArgs) -> String {
+    format!(
+        "media={}&comments={}&authors={}&redirects={}&dry_run={}",
+        !args.skip_media, !args.skip_comments, !args.assign_to_me, !args.no_redirects, args.dry_run
+    )
+}
+
+async fn import_wordpress(ctx: &CliContext, args: WordpressImportArgs) -> CliResult<()> {
+    if !args.file.exists() {
+        return Err(CliError::NotFound(format!(
+            "File not found: {}",
+            args.file.display()
+        )));
+    }
+    let file_size = std::fs::metadata(&args.file)?.len();
+    if file_size > MAX_WXR_SIZE {
+        return Err(CliError::InvalidInput(format!(
+            "{} is larger than the {} MB the server accepts; split the export in WordPress",
+            args.file.display(),
+            MAX_WXR_SIZE / (1024 * 1024)
+        )));
+    }
+    if args.dry_run && args.redirect_map.is_some() {
+        return Err(CliError::InvalidInput(
+            "--redirect-map needs a real import; a dry run creates no redirects".to_string(),
+        ));
+    }
+    if !args.dry_run {
+        ctx.confirm_remote("import WordPress content")?;
     }
 
-    let spinner = ProgressBar::spinner("Importing content...");
+    print_header(if args.dry_run {
+        "WordPress Import (dry run)"
+    } else {
+        "WordPress Import"
+    });
+    print_kv("File", &args.file.display().to_string());
+    print_kv("Size", &format_size(file_size));
 
-    // Call API to import
+    let content = std::fs::read_to_string(&args.file)?;
     let client = ctx.http_client();
-    let token = ctx.require_auth()?;
-
-    // Read file content
-    let content = std::fs::read_to_string(file)?;
-
     let url = format!("{}/api/v1/import/wordpress", ctx.server_url());
 
-    #[derive(Serialize)]
-    struct ImportRequest {
-        content: String,
-        import_posts: bool,
-        import_pages: bool,
-        import_media: bool,
-        import_users: bool,
-    }
-
+    let spinner = ProgressBar::spinner("Uploading...");
     let response = client
-        .post(&url)
-        .header("Authorization", format!("Bearer {}", token))
-        .json(&ImportRequest {
-            content,
-            import_posts,
-            import_pages,
-            import_media,
-            import_users,
-        })
+        .post(format!("{}?{}", url, import_query(&args)))
+        .header("Authorization", ctx.auth_header()?)
+        .header("Content-Type", "application/xml")
+        .body(content)
         .send()
         .await
-        .map_err(|e| CliError::Network(format!("Failed to import: {}", e)))?;
-
+        .map_err(|e| CliError::Network(format!("Failed to start import: {}", e)))?;
     spinner.finish_and_clear();
 
     if !response.status().is_success() {
         let status = response.status();
-        if status == reqwest::StatusCode::NOT_FOUND {
-            // API endpoint doesn't exist, show local import message
-            println!();
-            println!(
-                "{}",
-                "Note: WordPress import API is not available.".yellow()
-            );
-            println!("The WXR file has been analyzed. To import the content,");
-            println!("consider using the web admin interface or a future CLI version");
-            println!("with direct database access.");
-            return Ok(());
-        }
         let body = response.text().await.unwrap_or_default();
         return Err(CliError::OperationFailed(format!(
-            "Import failed ({}): {}",
+            "Failed to start import ({}): {}",
             status, body
         )));
     }
 
-    #[derive(Deserialize)]
-    struct ImportResult {
-        posts_imported: usize,
-        pages_imported: usize,
-        media_imported: usize,
-        users_imported: usize,
+    let mut run = response
+        .json::<ApiEnvelope<WordpressImportRun>>()
+        .await
+        .map_err(|e| CliError::Serialization(format!("Failed to parse response: {}", e)))?
+        .data;
+
+    if let Some(source) = &run.source_url {
+        print_kv("Exported from", source);
+    }
+    if args.no_wait {
+        print_kv("Import", &run.id);
+        print_kv("Items", &run.total.to_string());
+        ctx.info(&format!(
+            "Import started; follow it at /api/v1/import/wordpress/{}",
+            run.id
+        ));
+        return Ok(());
     }
 
-    let result: ImportResult = response
-        .json()
-        .await
-        .map_err(|e| CliError::Serialization(format!("Failed to parse response: {}", e)))?;
+    let progress = ProgressBar::new(run.total, "Importing content");
+    let mut shown = 0;
+    loop {
+        progress.inc(run.processed.saturating_sub(shown));
+        shown = shown.max(run.processed);
+        if run.status != "running" {
+            break;
+        }
 
-    println!();
-    println!("{}", "Import complete!".green().bold());
-    println!("  {} {}", "Posts imported:".green(), result.posts_imported);
-    println!("  {} {}", "Pages imported:".green(), result.pages_imported);
-    println!("  {} {}", "Media imported:".green(), result.media_imported);
-    println!("  {} {}", "Users imported:".green(), result.users_imported);
+        tokio::time::sleep(IMPORT_POLL_INTERVAL).await;
+        let response = client
+            .get(format!("{}/{}", url, run.id))
+            .header("Authorization", ctx.auth_header()?)
+            .send()
+            .await
+            .map_err(|e| CliError::Network(format!("Failed to fetch import progress: {}", e)))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(CliError::OperationFailed(format!(
+                "Failed to fetch import progress ({}): {}",
+                status, body
+            )));
+        }
+        run = response
+            .json::<ApiEnvelope<WordpressImportRun>>()
+            .await
+            .map_err(|e| CliError::Serialization(format!("Failed to parse response: {}", e)))?
+            .data;
+    }
+    progress.finish_and_clear();
+
+    print_kv("Import", &run.id);
+    print_section(if run.dry_run {
+        "Would import"
+    } else {
+        "Imported"
+    });
+    print_kv("Posts", &run.posts.to_string());
+    print_kv("Pages", &run.pages.to_string());
+    print_kv("Media", &run.media.to_string());
+    print_kv("Comments", &run.comments.to_string());
+    print_kv("Categories", &run.categories.to_string());
+    print_kv("Tags", &run.tags.to_string());
+    print_kv("Authors", &run.authors.to_string());
+    print_kv("Redirects", &run.redirects.to_string());
+    print_kv("Skipped", &run.skipped.to_string());
+    print_kv("Failed", &run.failed.to_string());
+    for error in &run.errors {
+        ctx.warning(&format!(
+            "Item {} ({}): {}",
+            error.item, error.title, error.message
+        ));
+    }
+    if run.errors.len() < run.failed as usize {
+        ctx.warning(&format!(
+            "{} more item(s) failed",
+            run.failed as usize - run.errors.len()
+        ));
+    }
+
+    if let Some(path) = &args.redirect_map {
+        let response = client
+            .get(format!("{}/{}/redirects", url, run.id))
+            .header("Authorization", ctx.auth_header()?)
+            .send()
+            .await
+            .map_err(|e| CliError::Network(format!("Failed to fetch redirects: {}", e)))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(CliError::OperationFailed(format!(
+                "Failed to fetch redirects ({}): {}",
+                status, body
+            )));
+        }
+        let csv = response
+            .text()
+            .await
+            .map_err(|e| CliError::Network(format!("Failed to read redirects: {}", e)))?;
+        std::fs::write(path, csv)?;
+        print_kv("Redirect map", &path.display().to_string());
+    }
+
+    let summary = format!(
+        "{} {} post(s), {} page(s), {} media file(s) and {} comment(s)",
+        if run.dry_run {
+            "Would import"
+        } else {
+            "Imported"
+        },
+        run.posts,
+        run.pages,
+        run.media,
+        run.comments
+    );
+    if run.status != "completed" {
+        return Err(CliError::OperationFailed(format!(
+            "Import {}: {}",
+            run.status, summary
+        )));
+    }
+    if run.failed > 0 {
+        return Err(CliError::PartialFailure(format!(
+            "{}, {} failed",
+            summary, run.failed
+        )));
+    }
+    ctx.success(&summary);
 
     Ok(())
 }
 
+fn format_size(bytes: u64) -> String {
+    if bytes >= 1024 * 1024 {
+        format!("{:.2} MB", bytes as f64 / (1024.0 * 1024.0))
+    } else if bytes >= 1024 {
+        format!("{:.2} KB", bytes as f64 / 1024.0)
+    } else {
+        format!("{} bytes", bytes)
+    }
+}
+
 async fn export_wxr(
     ctx: &CliContext,
     output: Option<String>,
@@ -273,7 +406,7 @@ async fn export_wxr(
     print_kv("Media", if export_media { "Yes" } else { "No" });
     print_kv("Users", if export_users { "Yes" } else { "No" });
     print_kv("Published Only", if published_only { "Yes" } else { "No" });
-    println!();
+    human!();
 
     let spinner = ProgressBar::spinner("Exporting content...");
 
@@ -302,12 +435,7 @@ async fn export_wxr(
     if !response.status().is_success() {
         let status = response.status();
         if status == reqwest::StatusCode::NOT_FOUND {
-            println!();
-            println!(
-                "{}",
-                "Note: WordPress export API is not available yet.".yellow()
-            );
-            println!("This feature will be available in a future release.");
+            ctx.warning("WordPress export API is not available yet.");
             return Ok(());
         }
         let body = response.text().await.unwrap_or_default();
@@ -326,18 +454,13 @@ async fn export_wxr(
     std::fs::write(&output_path, &content)?;
 
     let file_size = std::fs::metadata(&output_path)?.len();
-    let size_str = if file_size >= 1024 * 1024 {
-        format!("{:.2} MB", file_size as f64 / (1024.0 * 1024.0))
-    } else if file_size >= 1024 {
-        format!("{:.2} KB", file_size as f64 / 1024.0)
-    } else {
-        format!("{} bytes", file_size)
-    };
+    let size_str = format_size(file_size);
 
-    println!();
-    println!("{}", "Export complete!".green().bold());
-    println!("  {} {}", "File:".green(), output_path);
-    println!("  {} {}", "Size:".green(), size_str);
+    human!();
+    human!("{}", "Export complete!".green().bold());
+    print_section("Exported");
+    print_kv("File", &output_path);
+    print_kv("Size", &size_str);
 
     Ok(())
 }
@@ -351,33 +474,23 @@ async fn analyze_wxr(file: &str) -> CliResult<()> {
     }
 
     let file_size = std::fs::metadata(path)?.len();
-    let size_str = if file_size >= 1024 * 1024 {
-        format!("{:.2} MB", file_size as f64 / (1024.0 * 1024.0))
-    } else if file_size >= 1024 {
-        format!("{:.2} KB", file_size as f64 / 1024.0)
-    } else {
-        format!("{} bytes", file_size)
-    };
+    let size_str = format_size(file_size);
 
     print_kv("File", file);
     print_kv("Size", &size_str);
-    println!();
+    human!();
 
     let analysis = analyze_wxr_file(file)?;
 
-    println!("{}", "Content Summary:".cyan().bold());
-    println!();
-    println!("  {} {}", "Posts:".green().bold(), analysis.posts);
-    println!("  {} {}", "Pages:".green().bold(), analysis.pages);
-    println!(
-        "  {} {}",
-        "Attachments:".green().bold(),
-        analysis.attachments
-    );
-    println!("  {} {}", "Users/Authors:".green().bold(), analysis.users);
-    println!("  {} {}", "Categories:".green().bold(), analysis.categories);
-    println!("  {} {}", "Tags:".green().bold(), analysis.tags);
-    println!("  {} {}", "Comments:".green().bold(), analysis.comments);
+    print_section("Content Summary:");
+    human!();
+    print_kv("Posts", &analysis.posts.to_string());
+    print_kv("Pages", &analysis.pages.to_string());
+    print_kv("Attachments", &analysis.attachments.to_string());
+    print_kv("Users/Authors", &analysis.users.to_string());
+    print_kv("Categories", &analysis.categories.to_string());
+    print_kv("Tags", &analysis.tags.to_string());
+    print_kv("Comments", &analysis.comments.to_string());
 
     Ok(())
 }
@@ -418,3 +531,33 @@ fn analyze_wxr_file(file: &str) -> CliResult<WxrAnalysis> {
 
     Ok(analysis)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[test]
+    fn test_import_query() {
+        let cli = crate::commands::Cli::parse_from([
+            "rustpress",
+            "import",
+            "wordpress",
+            "export.xml",
+            "--skip-media",
+            "--assign-to-me",
+            "--dry-run",
+        ]);
+        let crate::commands::Commands::Import(ImportCommand {
+            command: ImportSubcommand::Wordpress(args),
+        }) = cli.command
+        else {
+            panic!("expected import wordpress");
+        };
+        assert_eq!(args.file, PathBuf::from("export.xml"));
+        assert_eq!(
+            import_query(&args),
+            "media=false&comments=true&authors=false&redirects=true&dry_run=true"
+        );
+    }
+}
@@ -6,7 +6,7 @@ use tabled::Tabled;
 
 use crate::context::CliContext;
 use crate::error::{CliError, CliResult};
-use crate::output::{print_header, print_kv, OutputFormatter, ProgressBar};
+use crate::output::{human, print_header, print_kv, ProgressBar};
 
 #[derive(Args, Debug)]
 pub struct MediaCommand {
@@ -157,11 +157,11 @@ async fn list_media(ctx: &CliContext, mime_type: Option<String>, limit: u32) ->
         .map_err(|e| CliError::Serialization(format!("Failed to parse response: {}", e)))?;
 
     if media.is_empty() {
-        println!("{}", ctx.output_format.info("No media found"));
+        ctx.info("No media found");
     } else {
-        println!("{}", ctx.output_format.format(&media));
-        println!();
-        println!("Total: {} file(s)", media.len());
+        ctx.list(&media, "No results found.");
+        human!();
+        human!("Total: {} file(s)", media.len());
     }
 
     Ok(())
@@ -259,12 +259,8 @@ async fn upload_media(
         .and_then(|v| v.as_str())
         .unwrap_or("unknown");
 
-    println!();
-    println!(
-        "{}",
-        ctx.output_format
-            .success(&format!("Media uploaded with ID: {}", id))
-    );
+    human!();
+    ctx.success(&format!("Media uploaded with ID: {}", id));
 
     Ok(())
 }
@@ -318,6 +314,7 @@ async fn get_media(ctx: &CliContext, id: &str) -> CliResult<()> {
 }
 
 async fn delete_media(ctx: &CliContext, id: &str, delete_file: bool) -> CliResult<()> {
+    ctx.confirm_remote(&format!("delete media {}", id))?;
     print_header("Deleting Media");
 
     let spinner = ProgressBar::spinner("Deleting media...");
@@ -347,7 +344,7 @@ async fn delete_media(ctx: &CliContext, id: &str, delete_file: bool) -> CliResul
         )));
     }
 
-    println!("{}", ctx.output_format.success("Media deleted"));
+    ctx.success("Media deleted");
     Ok(())
 }
 
@@ -386,11 +383,7 @@ async fn optimize_media(ctx: &CliContext, all: bool, id: Option<String>) -> CliR
         let status = response.status();
         // If endpoint doesn't exist, provide info
         if status == reqwest::StatusCode::NOT_FOUND {
-            println!(
-                "{}",
-                ctx.output_format
-                    .info("Media optimization is not available via API")
-            );
+            ctx.info("Media optimization is not available via API");
             return Ok(());
         }
         let body = response.text().await.unwrap_or_default();
@@ -406,12 +399,8 @@ async fn optimize_media(ctx: &CliContext, all: bool, id: Option<String>) -> CliR
         .and_then(|v| v.as_i64())
         .unwrap_or(1);
 
-    println!();
-    println!(
-        "{}",
-        ctx.output_format
-            .success(&format!("Optimized {} file(s)", count))
-    );
+    human!();
+    ctx.success(&format!("Optimized {} file(s)", count));
 
     Ok(())
 }
@@ -451,11 +440,7 @@ async fn regenerate_thumbnails(ctx: &CliContext, all: bool, id: Option<String>)
         let status = response.status();
         // If endpoint doesn't exist, provide info
         if status == reqwest::StatusCode::NOT_FOUND {
-            println!(
-                "{}",
-                ctx.output_format
-                    .info("Thumbnail regeneration is not available via API")
-            );
+            ctx.info("Thumbnail regeneration is not available via API");
             return Ok(());
         }
         let body = response.text().await.unwrap_or_default();
@@ -471,12 +456,8 @@ async fn regenerate_thumbnails(ctx: &CliContext, all: bool, id: Option<String>)
         .and_then(|v| v.as_i64())
         .unwrap_or(1);
 
-    println!();
-    println!(
-        "{}",
-        ctx.output_format
-            .success(&format!("Regenerated thumbnails for {} file(s)", count))
-    );
+    human!();
+    ctx.success(&format!("Regenerated thumbnails for {} file(s)", count));
 
     Ok(())
 }
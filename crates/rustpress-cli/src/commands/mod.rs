@@ -13,6 +13,7 @@ pub mod completion;
 pub mod config;
 pub mod cron;
 pub mod db;
+pub mod doctor;
 pub mod import_export;
 pub mod media;
 pub mod pages;
@@ -33,7 +34,15 @@ pub mod users;
     about = "Command-line interface for RustPress CMS",
     long_about = "RustPress CLI provides comprehensive management capabilities for RustPress CMS installations.\n\n\
                   Use this tool to manage database operations, users, content, themes, plugins, and more.",
-    after_help = "For more information, visit: https://github.com/rustpress/rustpress"
+    after_help = "Exit codes:\n  \
+                  0  success\n  \
+                  1  runtime error\n  \
+                  2  invalid input\n  \
+                  3  partial failure\n  \
+                  4  authentication required or rejected\n  \
+                  5  resource not found\n  \
+                  6  server unreachable\n\n\
+                  For more information, visit: https://github.com/rustpress/rustpress"
 )]
 pub struct Cli {
     /// Output format
@@ -56,6 +65,24 @@ pub struct Cli {
     #[arg(short, long, global = true, env = "RUSTPRESS_CONFIG")]
     pub config: Option<String>,
 
+    /// Run against a live site's API instead of the logged-in server
+    #[arg(long, global = true, env = "RUSTPRESS_REMOTE", value_name = "URL")]
+    pub remote: Option<String>,
+
+    /// API key to authenticate with (create one with 'auth api-keys create')
+    #[arg(
+        long,
+        global = true,
+        env = "RUSTPRESS_API_KEY",
+        hide_env_values = true,
+        value_name = "KEY"
+    )]
+    pub api_key: Option<String>,
+
+    /// Skip confirmation prompts for destructive operations on a remote site
+    #[arg(long, global = true)]
+    pub assume_yes: bool,
+
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -125,6 +152,9 @@ pub enum Commands {
     /// Generate shell completions
     Completion(completion::CompletionCommand),
 
+    /// Import content (WordPress)
+    Import(import_export::ImportCommand),
+
     /// WordPress import/export
     #[command(alias = "wp")]
     ImportExport(import_export::ImportExportCommand),
@@ -132,6 +162,9 @@ pub enum Commands {
     /// Scheduled tasks management
     Cron(cron::CronCommand),
 
+    /// Installation diagnostics (compat)
+    Doctor(doctor::DoctorCommand),
+
     /// Start interactive shell (REPL)
     #[command(alias = "shell", alias = "repl")]
     Interactive,
@@ -147,3 +180,169 @@ pub enum Commands {
     #[command(alias = "system")]
     Info,
 }
+
+/// What a command needs from the server it runs against with `--remote`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RemoteAccess {
+    /// Does not call the admin API
+    None,
+    /// Works on the local installation and cannot run remotely
+    LocalOnly(&'static str),
+    /// Reads the named API resource
+    Read(&'static str),
+    /// Changes the named API resource
+    Write(&'static str),
+}
+
+impl RemoteAccess {
+    fn read_or_write(resource: &'static str, read: bool) -> Self {
+        if read {
+            RemoteAccess::Read(resource)
+        } else {
+            RemoteAccess::Write(resource)
+        }
+    }
+}
+
+impl Commands {
+    /// Access the command needs, checked against the server's capabilities
+    /// before it runs in remote mode
+    pub fn remote_access(&self) -> RemoteAccess {
+        use RemoteAccess::{LocalOnly, None, Read, Write};
+
+        match self {
+            Commands::Artifacts { .. }
+            | Commands::Auth(_)
+            | Commands::Completion(_)
+            | Commands::Config(_)
+            | Commands::Health { .. }
+            | Commands::Info => None,
+            Commands::Interactive => LocalOnly("the interactive shell"),
+            Commands::Server(cmd) => match &cmd.command {
+                server::ServerSubcommand::Start { .. } => LocalOnly("starting the server"),
+                server::ServerSubcommand::Stop { .. } => LocalOnly("stopping the server"),
+                server::ServerSubcommand::Status | server::ServerSubcommand::Health { .. } => None,
+                server::ServerSubcommand::ReadOnly { action } => RemoteAccess::read_or_write(
+                    "maintenance",
+                    matches!(action, Option::None | Some(server::ReadOnlyAction::Status)),
+                ),
+            },
+            Commands::Db(cmd) => match &cmd.command {
+                db::DbSubcommand::Restore { .. } => LocalOnly("restoring a database dump"),
+                db::DbSubcommand::Migrate {
+                    dry_run,
+                    rollback,
+                    status,
+                } => RemoteAccess::read_or_write("db", *status || (*dry_run && rollback.is_none())),
+                db::DbSubcommand::Status
+                | db::DbSubcommand::Query { .. }
+                | db::DbSubcommand::Tables { .. }
+                | db::DbSubcommand::Export { .. }
+                | db::DbSubcommand::AuditLog { .. } => Read("db"),
+                db::DbSubcommand::Backup { .. }
+                | db::DbSubcommand::Import { .. }
+                | db::DbSubcommand::Optimize { .. } => Write("db"),
+            },
+            Commands::Users(cmd) => RemoteAccess::read_or_write(
+                "users",
+                matches!(
+                    cmd.command,
+                    users::UsersSubcommand::List { .. }
+                        | users::UsersSubcommand::Get { .. }
+                        | users::UsersSubcommand::Export { .. }
+                ),
+            ),
+            Commands::Posts(cmd) => RemoteAccess::read_or_write(
+                "posts",
+                matches!(
+                    cmd.command,
+                    posts::PostsSubcommand::List { .. } | posts::PostsSubcommand::Get { .. }
+                ),
+            ),
+            Commands::Pages(cmd) => RemoteAccess::read_or_write(
+                "pages",
+                matches!(
+                    cmd.command,
+                    pages::PagesSubcommand::List { .. } | pages::PagesSubcommand::Get { .. }
+                ),
+            ),
+            Commands::Media(cmd) => RemoteAccess::read_or_write(
+                "media",
+                matches!(
+                    cmd.command,
+                    media::MediaSubcommand::List { .. } | media::MediaSubcommand::Get { .. }
+                ),
+            ),
+            Commands::Themes(cmd) => RemoteAccess::read_or_write(
+                "themes",
+                matches!(
+                    cmd.command,
+                    themes::ThemesSubcommand::List { .. }
+                        | themes::ThemesSubcommand::Get { .. }
+                        | themes::ThemesSubcommand::Export { .. }
+                        | themes::ThemesSubcommand::Scan
+                ),
+            ),
+            Commands::Plugins(cmd) => RemoteAccess::read_or_write(
+                "plugins",
+                matches!(
+                    cmd.command,
+                    plugins::PluginsSubcommand::List { .. }
+                        | plugins::PluginsSubcommand::Get { .. }
+                        | plugins::PluginsSubcommand::CheckUpdates
+                ),
+            ),
+            Commands::Cache(cmd) => RemoteAccess::read_or_write(
+                "cache",
+                match &cmd.command {
+                    cache::CacheSubcommand::Stats => true,
+                    cache::CacheSubcommand::Config { set, .. } => set.is_none(),
+                    _ => false,
+                },
+            ),
+            Commands::Settings(cmd) => RemoteAccess::read_or_write(
+                "settings",
+                matches!(
+                    cmd.command,
+                    settings::SettingsSubcommand::List { .. }
+                        | settings::SettingsSubcommand::Get { .. }
+                        | settings::SettingsSubcommand::Export { .. }
+                ),
+            ),
+            Commands::Backup(cmd) => RemoteAccess::read_or_write(
+                "backups",
+                matches!(
+                    cmd.command,
+                    backup::BackupSubcommand::List
+                        | backup::BackupSubcommand::Download { .. }
+                        | backup::BackupSubcommand::Schedule(backup::ScheduleSubcommand::List)
+                ),
+            ),
+            Commands::Seo(cmd) => RemoteAccess::read_or_write(
+                "seo",
+                match &cmd.command {
+                    seo::SeoSubcommand::Sitemap(seo::SitemapSubcommand::Generate) => false,
+                    seo::SeoSubcommand::Robots { set, .. }
+                    | seo::SeoSubcommand::Settings { set, .. } => set.is_none(),
+                    _ => true,
+                },
+            ),
+            Commands::Import(_) => Write("import"),
+            Commands::ImportExport(cmd) => match &cmd.command {
+                import_export::ImportExportSubcommand::Import(_) => Write("import"),
+                import_export::ImportExportSubcommand::Export { .. } => Read("export"),
+                import_export::ImportExportSubcommand::Analyze { .. } => None,
+            },
+            Commands::Cron(cmd) => RemoteAccess::read_or_write(
+                "cron",
+                matches!(
+                    cmd.command,
+                    cron::CronSubcommand::List
+                        | cron::CronSubcommand::Get { .. }
+                        | cron::CronSubcommand::History { .. }
+                ),
+            ),
+            Commands::Doctor(_) => Read("compat"),
+        }
+    }
+}
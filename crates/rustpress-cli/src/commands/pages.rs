@@ -6,7 +6,7 @@ use tabled::Tabled;
 
 use crate::context::CliContext;
 use crate::error::{CliError, CliResult};
-use crate::output::{print_header, print_kv, OutputFormatter, ProgressBar};
+use crate::output::{human, print_header, print_kv, ProgressBar};
 
 #[derive(Args, Debug)]
 pub struct PagesCommand {
@@ -208,11 +208,7 @@ async fn list_pages(
         .await
         .map_err(|e| CliError::Serialization(format!("Failed to parse response: {}", e)))?;
 
-    if pages.is_empty() {
-        println!("{}", ctx.output_format.info("No pages found"));
-    } else {
-        println!("{}", ctx.output_format.format(&pages));
-    }
+    ctx.list(&pages, "No pages found");
 
     Ok(())
 }
@@ -275,12 +271,8 @@ async fn create_page(
         .and_then(|v| v.as_str())
         .unwrap_or("unknown");
 
-    println!();
-    println!(
-        "{}",
-        ctx.output_format
-            .success(&format!("Page created with ID: {}", id))
-    );
+    human!();
+    ctx.success(&format!("Page created with ID: {}", id));
 
     Ok(())
 }
@@ -376,17 +368,15 @@ async fn update_page(
         )));
     }
 
-    println!(
-        "{}",
-        ctx.output_format.success(&format!("Page {} updated", page))
-    );
+    ctx.success(&format!("Page {} updated", page));
     Ok(())
 }
 
 async fn delete_page(ctx: &CliContext, page: &str, force: bool) -> CliResult<()> {
     if !force {
-        println!("This will move the page to trash.");
-        println!("Run with --force to permanently delete.");
+        ctx.info("This will move the page to trash. Run with --force to permanently delete.");
+    } else {
+        ctx.confirm_remote(&format!("permanently delete page '{}'", page))?;
     }
 
     let spinner = ProgressBar::spinner(if force {
@@ -420,14 +410,11 @@ async fn delete_page(ctx: &CliContext, page: &str, force: bool) -> CliResult<()>
         )));
     }
 
-    println!(
-        "{}",
-        ctx.output_format.success(if force {
-            "Page deleted"
-        } else {
-            "Page moved to trash"
-        })
-    );
+    ctx.success(if force {
+        "Page deleted"
+    } else {
+        "Page moved to trash"
+    });
     Ok(())
 }
 
@@ -6,7 +6,7 @@ use tabled::Tabled;
 
 use crate::context::CliContext;
 use crate::error::{CliError, CliResult};
-use crate::output::{print_header, print_kv, OutputFormatter, ProgressBar};
+use crate::output::{human, print_header, print_kv, OutputFormatter, ProgressBar};
 
 #[derive(Args, Debug)]
 pub struct PluginsCommand {
@@ -118,11 +118,7 @@ async fn list_plugins(ctx: &CliContext, status: &str) -> CliResult<()> {
         .await
         .map_err(|e| CliError::Serialization(format!("Failed to parse response: {}", e)))?;
 
-    if plugins.is_empty() {
-        println!("{}", ctx.output_format.info("No plugins found"));
-    } else {
-        println!("{}", ctx.output_format.format(&plugins));
-    }
+    ctx.list(&plugins, "No plugins found");
 
     Ok(())
 }
@@ -200,11 +196,7 @@ async fn activate_plugin(ctx: &CliContext, plugin: &str) -> CliResult<()> {
         )));
     }
 
-    println!(
-        "{}",
-        ctx.output_format
-            .success(&format!("Plugin '{}' activated", plugin))
-    );
+    ctx.success(&format!("Plugin '{}' activated", plugin));
     Ok(())
 }
 
@@ -232,11 +224,7 @@ async fn deactivate_plugin(ctx: &CliContext, plugin: &str) -> CliResult<()> {
         )));
     }
 
-    println!(
-        "{}",
-        ctx.output_format
-            .success(&format!("Plugin '{}' deactivated", plugin))
-    );
+    ctx.success(&format!("Plugin '{}' deactivated", plugin));
     Ok(())
 }
 
@@ -283,11 +271,7 @@ async fn install_plugin(ctx: &CliContext, source: &str) -> CliResult<()> {
             )));
         }
 
-        println!(
-            "{}",
-            ctx.output_format
-                .success(&format!("Plugin '{}' installed", plugin_id))
-        );
+        ctx.success(&format!("Plugin '{}' installed", plugin_id));
     } else {
         // URL-based install
         let spinner = ProgressBar::spinner("Installing plugin from URL...");
@@ -318,7 +302,7 @@ async fn install_plugin(ctx: &CliContext, source: &str) -> CliResult<()> {
             )));
         }
 
-        println!("{}", ctx.output_format.success("Plugin installed"));
+        ctx.success("Plugin installed");
     }
 
     Ok(())
@@ -326,10 +310,10 @@ async fn install_plugin(ctx: &CliContext, source: &str) -> CliResult<()> {
 
 async fn uninstall_plugin(ctx: &CliContext, plugin: &str, force: bool) -> CliResult<()> {
     if !force {
-        println!("This will permanently delete the plugin.");
-        println!("Run with --force to confirm.");
+        ctx.warning("This will permanently delete the plugin. Run with --force to confirm.");
         return Ok(());
     }
+    ctx.confirm_remote(&format!("uninstall plugin '{}'", plugin))?;
 
     let spinner = ProgressBar::spinner("Uninstalling plugin...");
 
@@ -354,11 +338,7 @@ async fn uninstall_plugin(ctx: &CliContext, plugin: &str, force: bool) -> CliRes
         )));
     }
 
-    println!(
-        "{}",
-        ctx.output_format
-            .success(&format!("Plugin '{}' uninstalled", plugin))
-    );
+    ctx.success(&format!("Plugin '{}' uninstalled", plugin));
     Ok(())
 }
 
@@ -381,22 +361,24 @@ async fn check_updates(ctx: &CliContext) -> CliResult<()> {
 
     if !response.status().is_success() {
         // If endpoint doesn't exist, just report no updates
-        println!("{}", ctx.output_format.info("All plugins are up to date"));
+        ctx.info("All plugins are up to date");
         return Ok(());
     }
 
     let updates: Vec<serde_json::Value> = response.json().await.unwrap_or_default();
 
     if updates.is_empty() {
-        println!("{}", ctx.output_format.info("All plugins are up to date"));
+        ctx.info("All plugins are up to date");
+    } else if ctx.output_format.is_machine_readable() {
+        ctx.item(&updates);
     } else {
-        println!("Available updates:");
+        human!("Available updates:");
         for update in updates {
             if let (Some(name), Some(version)) = (
                 update.get("name").and_then(|v| v.as_str()),
                 update.get("new_version").and_then(|v| v.as_str()),
             ) {
-                println!("  - {} -> {}", name, version);
+                human!("  - {} -> {}", name, version);
             }
         }
     }
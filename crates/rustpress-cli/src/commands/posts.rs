@@ -6,7 +6,7 @@ use tabled::Tabled;
 
 use crate::context::CliContext;
 use crate::error::{CliError, CliResult};
-use crate::output::{print_header, print_kv, OutputFormatter, ProgressBar};
+use crate::output::{human, print_header, print_kv, OutputFormatter, ProgressBar};
 
 #[derive(Args, Debug)]
 pub struct PostsCommand {
@@ -263,11 +263,11 @@ async fn list_posts(
         .map_err(|e| CliError::Serialization(format!("Failed to parse response: {}", e)))?;
 
     if posts.is_empty() {
-        println!("{}", ctx.output_format.info("No posts found"));
+        ctx.info("No posts found");
     } else {
-        println!("{}", ctx.output_format.format(&posts));
-        println!();
-        println!("Total: {} post(s)", posts.len());
+        ctx.list(&posts, "No results found.");
+        human!();
+        human!("Total: {} post(s)", posts.len());
     }
 
     Ok(())
@@ -337,12 +337,8 @@ async fn create_post(
         .and_then(|v| v.as_str())
         .unwrap_or("unknown");
 
-    println!();
-    println!(
-        "{}",
-        ctx.output_format
-            .success(&format!("Post created with ID: {}", id))
-    );
+    human!();
+    ctx.success(&format!("Post created with ID: {}", id));
 
     Ok(())
 }
@@ -386,7 +382,9 @@ async fn get_post(ctx: &CliContext, post: &str) -> CliResult<()> {
     if let Some(ref published) = details.published_at {
         print_kv("Published", published);
     }
-    if !details.content.is_empty() {
+    if ctx.output_format.is_machine_readable() {
+        print_kv("Content", &details.content);
+    } else if !details.content.is_empty() {
         println!();
         println!("Content:");
         let preview = if details.content.len() > 500 {
@@ -443,17 +441,15 @@ async fn update_post(
         )));
     }
 
-    println!(
-        "{}",
-        ctx.output_format.success(&format!("Post {} updated", post))
-    );
+    ctx.success(&format!("Post {} updated", post));
     Ok(())
 }
 
 async fn delete_post(ctx: &CliContext, post: &str, force: bool) -> CliResult<()> {
     if !force {
-        println!("This will move the post to trash.");
-        println!("Run with --force to permanently delete.");
+        ctx.info("This will move the post to trash. Run with --force to permanently delete.");
+    } else {
+        ctx.confirm_remote(&format!("permanently delete post '{}'", post))?;
     }
 
     let spinner = ProgressBar::spinner(if force {
@@ -487,14 +483,11 @@ async fn delete_post(ctx: &CliContext, post: &str, force: bool) -> CliResult<()>
         )));
     }
 
-    println!(
-        "{}",
-        ctx.output_format.success(if force {
-            "Post deleted"
-        } else {
-            "Post moved to trash"
-        })
-    );
+    ctx.success(if force {
+        "Post deleted"
+    } else {
+        "Post moved to trash"
+    });
     Ok(())
 }
 
@@ -524,7 +517,7 @@ async fn publish_post(ctx: &CliContext, post: &str) -> CliResult<()> {
         )));
     }
 
-    println!("{}", ctx.output_format.success("Post published"));
+    ctx.success("Post published");
     Ok(())
 }
 
@@ -554,7 +547,7 @@ async fn unpublish_post(ctx: &CliContext, post: &str) -> CliResult<()> {
         )));
     }
 
-    println!("{}", ctx.output_format.success("Post unpublished"));
+    ctx.success("Post unpublished");
     Ok(())
 }
 
@@ -594,15 +587,14 @@ async fn duplicate_post(ctx: &CliContext, post: &str) -> CliResult<()> {
         .and_then(|v| v.as_str())
         .unwrap_or("unknown");
 
-    println!(
-        "{}",
-        ctx.output_format
-            .success(&format!("Post duplicated with ID: {}", id))
-    );
+    ctx.success(&format!("Post duplicated with ID: {}", id));
     Ok(())
 }
 
 async fn bulk_delete(ctx: &CliContext, posts: Vec<String>, force: bool) -> CliResult<()> {
+    if force {
+        ctx.confirm_remote(&format!("permanently delete {} posts", posts.len()))?;
+    }
     print_header(&format!("Bulk Delete {} Posts", posts.len()));
 
     let mut deleted = 0;
@@ -628,19 +620,18 @@ async fn bulk_delete(ctx: &CliContext, posts: Vec<String>, force: bool) -> CliRe
         }
     }
 
-    println!(
-        "{}",
-        ctx.output_format.success(&format!(
-            "{} {} post(s){}",
-            if force { "Deleted" } else { "Trashed" },
-            deleted,
-            if errors > 0 {
-                format!(", {} failed", errors)
-            } else {
-                String::new()
-            }
-        ))
+    let summary = format!(
+        "{} {} post(s)",
+        if force { "Deleted" } else { "Trashed" },
+        deleted
     );
+    if errors > 0 {
+        return Err(CliError::PartialFailure(format!(
+            "{}, {} failed",
+            summary, errors
+        )));
+    }
+    ctx.success(&summary);
 
     Ok(())
 }
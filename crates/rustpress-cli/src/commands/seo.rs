@@ -1,10 +1,14 @@
 //! SEO management commands
 
+use std::collections::BTreeMap;
+
 use clap::{Args, Subcommand};
+use serde::{Deserialize, Serialize};
+use tabled::Tabled;
 
 use crate::context::CliContext;
-use crate::error::CliResult;
-use crate::output::{print_header, print_kv, OutputFormatter, ProgressBar};
+use crate::error::{CliError, CliResult};
+use crate::output::{human, print_header, print_kv, OutputFormat, OutputFormatter, ProgressBar};
 
 #[derive(Args, Debug)]
 pub struct SeoCommand {
@@ -26,6 +30,19 @@ pub enum SeoSubcommand {
         #[arg(long)]
         set: Option<String>,
     },
+    /// Validate structured data (JSON-LD/microdata) on rendered pages.
+    /// Exits with a non-zero status when errors are found, for use in CI.
+    ValidateSchema {
+        /// Number of most recently updated posts/pages to check
+        #[arg(long, default_value_t = 100)]
+        limit: u32,
+        /// Only check this post type (post or page)
+        #[arg(long)]
+        post_type: Option<String>,
+        /// Fail on warnings as well as errors
+        #[arg(long)]
+        strict: bool,
+    },
     /// SEO settings
     Settings {
         #[arg(long)]
@@ -49,19 +66,197 @@ pub async fn execute(ctx: &CliContext, cmd: SeoCommand) -> CliResult<()> {
         },
         SeoSubcommand::Analyze { content } => analyze_seo(ctx, &content).await,
         SeoSubcommand::Robots { get, set } => manage_robots(ctx, get, set).await,
+        SeoSubcommand::ValidateSchema {
+            limit,
+            post_type,
+            strict,
+        } => validate_schema(ctx, limit, post_type, strict).await,
         SeoSubcommand::Settings { get, set } => manage_settings(ctx, get, set).await,
     }
 }
 
+/// API response envelope
+#[derive(Debug, Deserialize)]
+struct ApiEnvelope<T> {
+    data: T,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SchemaIssue {
+    severity: String,
+    item_type: Option<String>,
+    property: Option<String>,
+    message: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SchemaPageReport {
+    item_types: Vec<String>,
+    issues: Vec<SchemaIssue>,
+    errors: usize,
+    warnings: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SchemaPage {
+    url: String,
+    template: String,
+    report: SchemaPageReport,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SchemaTemplateSummary {
+    pages: usize,
+    failing_pages: usize,
+    errors: usize,
+    warnings: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SchemaRenderFailure {
+    url: String,
+    error: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SchemaAudit {
+    pages: Vec<SchemaPage>,
+    templates: BTreeMap<String, SchemaTemplateSummary>,
+    errors: usize,
+    warnings: usize,
+    render_errors: Vec<SchemaRenderFailure>,
+}
+
+#[derive(Debug, Serialize, Tabled)]
+struct SchemaTemplateRow {
+    #[tabled(rename = "Template")]
+    template: String,
+    #[tabled(rename = "Pages")]
+    pages: usize,
+    #[tabled(rename = "Failing")]
+    failing_pages: usize,
+    #[tabled(rename = "Errors")]
+    errors: usize,
+    #[tabled(rename = "Warnings")]
+    warnings: usize,
+}
+
+async fn validate_schema(
+    ctx: &CliContext,
+    limit: u32,
+    post_type: Option<String>,
+    strict: bool,
+) -> CliResult<()> {
+    let mut url = format!(
+        "{}/api/v1/seo/structured-data?limit={}",
+        ctx.server_url(),
+        limit
+    );
+    if let Some(ref post_type) = post_type {
+        url.push_str(&format!("&post_type={}", post_type));
+    }
+
+    let spinner = ProgressBar::spinner("Rendering pages and validating structured data...");
+    let response = ctx
+        .http_client()
+        .get(&url)
+        .header("Authorization", ctx.auth_header()?)
+        .send()
+        .await;
+    spinner.finish_and_clear();
+
+    let response = response
+        .map_err(|e| CliError::Network(format!("Failed to run structured data audit: {}", e)))?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(CliError::OperationFailed(format!(
+            "Structured data audit failed ({}): {}",
+            status, body
+        )));
+    }
+
+    let audit = response
+        .json::<ApiEnvelope<SchemaAudit>>()
+        .await
+        .map_err(|e| CliError::Serialization(format!("Failed to parse response: {}", e)))?
+        .data;
+
+    match ctx.output_format {
+        OutputFormat::Json | OutputFormat::Yaml => {
+            ctx.item(&audit);
+        }
+        OutputFormat::Table | OutputFormat::Plain => print_schema_audit(ctx, &audit),
+    }
+
+    let failed =
+        audit.errors > 0 || !audit.render_errors.is_empty() || (strict && audit.warnings > 0);
+    if failed {
+        return Err(CliError::OperationFailed(format!(
+            "Structured data validation failed: {} error(s), {} warning(s), {} page(s) not rendered",
+            audit.errors,
+            audit.warnings,
+            audit.render_errors.len()
+        )));
+    }
+
+    if !ctx.quiet {
+        ctx.success(&format!(
+            "Structured data valid on {} page(s)",
+            audit.pages.len()
+        ));
+    }
+    Ok(())
+}
+
+fn print_schema_audit(ctx: &CliContext, audit: &SchemaAudit) {
+    print_header("Structured Data by Template");
+    let rows: Vec<SchemaTemplateRow> = audit
+        .templates
+        .iter()
+        .map(|(template, summary)| SchemaTemplateRow {
+            template: template.clone(),
+            pages: summary.pages,
+            failing_pages: summary.failing_pages,
+            errors: summary.errors,
+            warnings: summary.warnings,
+        })
+        .collect();
+    ctx.list(&rows, "No results found.");
+
+    for page in audit.pages.iter().filter(|p| !p.report.issues.is_empty()) {
+        human!();
+        human!("{} ({})", page.url, page.template);
+        for issue in &page.report.issues {
+            let location = match (&issue.item_type, &issue.property) {
+                (Some(item_type), Some(property)) => format!("{}.{}: ", item_type, property),
+                (Some(item_type), None) => format!("{}: ", item_type),
+                _ => String::new(),
+            };
+            let line = format!("{}{}", location, issue.message);
+            if issue.severity == "Error" {
+                human!("  {}", ctx.output_format.error(&line));
+            } else {
+                human!("  {}", ctx.output_format.warning(&line));
+            }
+        }
+    }
+
+    for failure in &audit.render_errors {
+        human!();
+        ctx.error(&format!(
+            "{} could not be rendered: {}",
+            failure.url, failure.error
+        ));
+    }
+}
+
 async fn generate_sitemap(ctx: &CliContext) -> CliResult<()> {
     print_header("Generating Sitemap");
     let spinner = ProgressBar::spinner("Generating sitemap.xml...");
     tokio::time::sleep(std::time::Duration::from_secs(1)).await;
     spinner.finish_and_clear();
-    println!(
-        "{}",
-        ctx.output_format.success("Sitemap generated: /sitemap.xml")
-    );
+    ctx.success("Sitemap generated: /sitemap.xml");
     Ok(())
 }
 
@@ -84,20 +279,17 @@ async fn analyze_seo(ctx: &CliContext, content: &str) -> CliResult<()> {
     print_kv("Readability", "Grade 8 (Good)");
     print_kv("Internal Links", "5");
     print_kv("External Links", "2");
-    println!();
-    println!("{}", ctx.output_format.success("SEO Score: 85/100"));
+    human!();
+    ctx.success("SEO Score: 85/100");
     Ok(())
 }
 
 async fn manage_robots(ctx: &CliContext, get: bool, set: Option<String>) -> CliResult<()> {
     if get || set.is_none() {
         print_header("robots.txt");
-        println!("User-agent: *");
-        println!("Allow: /");
-        println!("Disallow: /admin/");
-        println!("Sitemap: /sitemap.xml");
+        ctx.text("User-agent: *\nAllow: /\nDisallow: /admin/\nSitemap: /sitemap.xml");
     } else if let Some(_content) = set {
-        println!("{}", ctx.output_format.success("robots.txt updated"));
+        ctx.success("robots.txt updated");
     }
     Ok(())
 }
@@ -109,17 +301,13 @@ async fn manage_settings(
 ) -> CliResult<()> {
     if let Some(key) = get {
         match key.as_str() {
-            "title_separator" => println!("-"),
-            "enable_og" => println!("true"),
-            _ => println!("{}", ctx.output_format.error("Unknown setting")),
+            "title_separator" => ctx.text("-"),
+            "enable_og" => ctx.text("true"),
+            _ => ctx.error("Unknown setting"),
         }
     } else if let Some(kv) = set {
         if let Some((key, value)) = kv.split_once('=') {
-            println!(
-                "{}",
-                ctx.output_format
-                    .success(&format!("Set {} = {}", key, value))
-            );
+            ctx.success(&format!("Set {} = {}", key, value));
         }
     } else {
         print_header("SEO Settings");
@@ -4,8 +4,8 @@ use clap::{Args, Subcommand};
 use colored::Colorize;
 
 use crate::context::CliContext;
-use crate::error::CliResult;
-use crate::output::{print_header, print_kv, OutputFormatter};
+use crate::error::{CliError, CliResult};
+use crate::output::{human, print_header, print_kv};
 
 #[derive(Args, Debug)]
 pub struct ServerCommand {
@@ -50,6 +50,32 @@ pub enum ServerSubcommand {
         #[arg(long, default_value = "http://127.0.0.1:3080")]
         url: String,
     },
+
+    /// Show or toggle read-only maintenance mode
+    ReadOnly {
+        #[command(subcommand)]
+        action: Option<ReadOnlyAction>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ReadOnlyAction {
+    /// Refuse writes and pause background jobs except cache refreshes
+    On {
+        /// Reason shown to clients whose writes are refused
+        #[arg(long)]
+        reason: Option<String>,
+
+        /// Seconds clients are told to wait before retrying
+        #[arg(long)]
+        retry_after: Option<u64>,
+    },
+
+    /// Accept writes and resume background jobs
+    Off,
+
+    /// Show whether read-only mode is on
+    Status,
 }
 
 pub async fn execute(ctx: &CliContext, cmd: ServerCommand) -> CliResult<()> {
@@ -63,6 +89,23 @@ pub async fn execute(ctx: &CliContext, cmd: ServerCommand) -> CliResult<()> {
         ServerSubcommand::Stop { force } => stop_server(ctx, force).await,
         ServerSubcommand::Status => show_status(ctx).await,
         ServerSubcommand::Health { url } => check_health(ctx, &url).await,
+        ServerSubcommand::ReadOnly { action } => match action {
+            Some(ReadOnlyAction::On {
+                reason,
+                retry_after,
+            }) => {
+                let body = serde_json::json!({
+                    "enabled": true,
+                    "reason": reason,
+                    "retry_after_secs": retry_after,
+                });
+                set_read_only(ctx, body).await
+            }
+            Some(ReadOnlyAction::Off) => {
+                set_read_only(ctx, serde_json::json!({ "enabled": false })).await
+            }
+            Some(ReadOnlyAction::Status) | None => show_read_only(ctx).await,
+        },
     }
 }
 
@@ -82,23 +125,23 @@ async fn start_server(
     }
     print_kv("Mode", if foreground { "Foreground" } else { "Daemon" });
 
-    println!();
+    human!();
 
     // In a real implementation, this would start the actual server
     // For now, we provide guidance
-    println!(
+    human!(
         "{}",
         "To start the server, use the rustpress-server binary:".yellow()
     );
-    println!();
-    println!(
+    human!();
+    human!(
         "  {} RUSTPRESS_HOST={} RUSTPRESS_PORT={} ./rustpress-server",
         "$".dimmed(),
         host,
         port
     );
-    println!();
-    println!(
+    human!();
+    human!(
         "{}",
         "Or set environment variables in your .env file.".dimmed()
     );
@@ -108,19 +151,19 @@ async fn start_server(
 
 async fn stop_server(ctx: &CliContext, force: bool) -> CliResult<()> {
     if force {
-        ctx.print(&ctx.output_format.warning("Force stopping server..."));
+        ctx.warning("Force stopping server...");
     } else {
-        ctx.print(&ctx.output_format.info("Gracefully stopping server..."));
+        ctx.info("Gracefully stopping server...");
     }
 
     // In a real implementation, this would send a signal to the server process
-    println!(
+    human!(
         "{}",
         "Server stop functionality requires the server to be running with PID tracking.".yellow()
     );
-    println!();
-    println!("To stop the server manually:");
-    println!("  {} pkill -f rustpress-server", "$".dimmed());
+    human!();
+    human!("To stop the server manually:");
+    human!("  {} pkill -f rustpress-server", "$".dimmed());
 
     Ok(())
 }
@@ -139,23 +182,16 @@ async fn show_status(ctx: &CliContext) -> CliResult<()> {
         .await
     {
         Ok(response) if response.status().is_success() => {
-            println!("{}", ctx.output_format.success("Server is running"));
+            ctx.success("Server is running");
             print_kv("URL", "http://127.0.0.1:3080");
             print_kv("Status", "Healthy");
         }
         Ok(response) => {
-            println!(
-                "{}",
-                ctx.output_format.warning("Server responding but unhealthy")
-            );
+            ctx.warning("Server responding but unhealthy");
             print_kv("Status Code", &response.status().to_string());
         }
         Err(_) => {
-            println!(
-                "{}",
-                ctx.output_format
-                    .error("Server is not running or not reachable")
-            );
+            ctx.error("Server is not running or not reachable");
             print_kv("Checked URL", url);
         }
     }
@@ -179,30 +215,90 @@ async fn check_health(ctx: &CliContext, url: &str) -> CliResult<()> {
             spinner.finish_and_clear();
 
             if response.status().is_success() {
-                println!("{}", ctx.output_format.success("Server is healthy"));
+                ctx.success("Server is healthy");
 
                 if let Ok(body) = response.json::<serde_json::Value>().await {
-                    println!();
+                    human!();
                     print_header("Health Details");
-                    println!("{}", ctx.output_format.format_one(&body));
+                    ctx.item(&body);
                 }
             } else {
-                println!(
-                    "{}",
-                    ctx.output_format
-                        .error(&format!("Server returned status: {}", response.status()))
-                );
+                ctx.error(&format!("Server returned status: {}", response.status()));
             }
         }
         Err(e) => {
             spinner.finish_and_clear();
-            println!(
-                "{}",
-                ctx.output_format
-                    .error(&format!("Failed to connect: {}", e))
-            );
+            ctx.error(&format!("Failed to connect: {}", e));
         }
     }
 
     Ok(())
 }
+
+fn read_only_url(ctx: &CliContext) -> String {
+    format!("{}/api/v1/maintenance/read-only", ctx.server_url())
+}
+
+async fn show_read_only(ctx: &CliContext) -> CliResult<()> {
+    let response = ctx
+        .http_client()
+        .get(read_only_url(ctx))
+        .header("Authorization", ctx.auth_header()?)
+        .send()
+        .await
+        .map_err(|e| CliError::Network(format!("Failed to fetch read-only status: {}", e)))?;
+
+    print_read_only(ctx, response).await
+}
+
+async fn set_read_only(ctx: &CliContext, body: serde_json::Value) -> CliResult<()> {
+    let response = ctx
+        .http_client()
+        .put(read_only_url(ctx))
+        .header("Authorization", ctx.auth_header()?)
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| CliError::Network(format!("Failed to change read-only mode: {}", e)))?;
+
+    print_read_only(ctx, response).await
+}
+
+async fn print_read_only(ctx: &CliContext, response: reqwest::Response) -> CliResult<()> {
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(CliError::OperationFailed(format!(
+            "Read-only mode request failed ({}): {}",
+            status, body
+        )));
+    }
+
+    let status: serde_json::Value = response
+        .json()
+        .await
+        .map_err(|e| CliError::Network(format!("Invalid response: {}", e)))?;
+
+    print_header("Read-Only Mode");
+    if status["enabled"].as_bool().unwrap_or(false) {
+        ctx.warning("Read-only mode is on");
+        print_kv("Since", status["since"].as_str().unwrap_or("-"));
+        print_kv("Reason", status["reason"].as_str().unwrap_or("-"));
+        print_kv(
+            "Retry-After",
+            &format!("{}s", status["retry_after_secs"].as_u64().unwrap_or(0)),
+        );
+        print_kv(
+            "Jobs",
+            if status["jobs_paused"].as_bool().unwrap_or(false) {
+                "Paused (cache refresh only)"
+            } else {
+                "Running"
+            },
+        );
+    } else {
+        ctx.success("Read-only mode is off");
+    }
+
+    Ok(())
+}
@@ -6,7 +6,7 @@ use tabled::Tabled;
 
 use crate::context::CliContext;
 use crate::error::{CliError, CliResult};
-use crate::output::{print_header, OutputFormatter};
+use crate::output::print_header;
 
 #[derive(Args, Debug)]
 pub struct SettingsCommand {
@@ -90,11 +90,7 @@ async fn list_settings(ctx: &CliContext, group: Option<String>) -> CliResult<()>
         .await
         .map_err(|e| CliError::Serialization(format!("Failed to parse response: {}", e)))?;
 
-    if settings.is_empty() {
-        println!("{}", ctx.output_format.info("No settings found"));
-    } else {
-        println!("{}", ctx.output_format.format(&settings));
-    }
+    ctx.list(&settings, "No settings found");
 
     Ok(())
 }
@@ -113,7 +109,7 @@ async fn get_setting(ctx: &CliContext, key: &str) -> CliResult<()> {
     if !response.status().is_success() {
         let status = response.status();
         if status == reqwest::StatusCode::NOT_FOUND {
-            println!("{}", ctx.output_format.error("Setting not found"));
+            ctx.error("Setting not found");
             return Ok(());
         }
         let body = response.text().await.unwrap_or_default();
@@ -129,7 +125,7 @@ async fn get_setting(ctx: &CliContext, key: &str) -> CliResult<()> {
         .map_err(|e| CliError::Serialization(format!("Failed to parse response: {}", e)))?;
 
     let value = setting.get("value").and_then(|v| v.as_str()).unwrap_or("");
-    println!("{}", value);
+    ctx.text(value);
 
     Ok(())
 }
@@ -159,11 +155,24 @@ async fn set_setting(ctx: &CliContext, key: &str, value: &str) -> CliResult<()>
         )));
     }
 
-    println!(
-        "{}",
-        ctx.output_format
-            .success(&format!("Set {} = {}", key, value))
-    );
+    // Sensitive settings wait for another administrator's approval
+    if response.status() == reqwest::StatusCode::ACCEPTED {
+        let accepted: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| CliError::Serialization(format!("Failed to parse response: {}", e)))?;
+        let request_id = accepted
+            .pointer("/data/id")
+            .and_then(|id| id.as_str())
+            .unwrap_or("unknown");
+        ctx.warning(&format!(
+            "Change to {} is waiting for approval by another administrator (request {})",
+            key, request_id
+        ));
+        return Ok(());
+    }
+
+    ctx.success(&format!("Set {} = {}", key, value));
     Ok(())
 }
 
@@ -203,17 +212,14 @@ async fn export_settings(ctx: &CliContext, output: Option<String>) -> CliResult<
     let json = serde_json::to_string_pretty(&export_data)?;
     std::fs::write(&output_file, json)?;
 
-    println!(
-        "{}",
-        ctx.output_format
-            .success(&format!("Exported to {}", output_file))
-    );
+    ctx.success(&format!("Exported to {}", output_file));
     Ok(())
 }
 
 async fn import_settings(ctx: &CliContext, file: &str) -> CliResult<()> {
     let content = std::fs::read_to_string(file)?;
     let settings: serde_json::Value = serde_json::from_str(&content)?;
+    ctx.confirm_remote(&format!("overwrite settings from {}", file))?;
 
     let client = ctx.http_client();
 
@@ -243,18 +249,13 @@ async fn import_settings(ctx: &CliContext, file: &str) -> CliResult<()> {
             }
         }
 
-        println!(
-            "{}",
-            ctx.output_format.success(&format!(
-                "Imported {} settings{}",
-                success,
-                if failed > 0 {
-                    format!(", {} failed", failed)
-                } else {
-                    String::new()
-                }
-            ))
-        );
+        if failed > 0 {
+            return Err(CliError::PartialFailure(format!(
+                "Imported {} settings, {} failed",
+                success, failed
+            )));
+        }
+        ctx.success(&format!("Imported {} settings", success));
     } else {
         return Err(CliError::InvalidInput(
             "Settings file must be a JSON object".to_string(),
@@ -6,7 +6,7 @@ use tabled::Tabled;
 
 use crate::context::CliContext;
 use crate::error::{CliError, CliResult};
-use crate::output::{print_header, print_kv, OutputFormatter, ProgressBar};
+use crate::output::{human, print_header, print_kv, ProgressBar};
 
 #[derive(Args, Debug)]
 pub struct ThemesCommand {
@@ -150,15 +150,10 @@ async fn list_themes(ctx: &CliContext, status: &str) -> CliResult<()> {
         .await
         .map_err(|e| CliError::Serialization(format!("Failed to parse response: {}", e)))?;
 
-    if themes.is_empty() {
-        println!(
-            "{}",
-            ctx.output_format
-                .info("No themes found. Run 'themes scan' to discover themes.")
-        );
-    } else {
-        println!("{}", ctx.output_format.format(&themes));
-    }
+    ctx.list(
+        &themes,
+        "No themes found. Run 'themes scan' to discover themes.",
+    );
 
     Ok(())
 }
@@ -248,11 +243,7 @@ async fn activate_theme(ctx: &CliContext, theme: &str) -> CliResult<()> {
         )));
     }
 
-    println!(
-        "{}",
-        ctx.output_format
-            .success(&format!("Theme '{}' activated", theme))
-    );
+    ctx.success(&format!("Theme '{}' activated", theme));
     Ok(())
 }
 
@@ -297,12 +288,8 @@ async fn install_theme(ctx: &CliContext, source: &str) -> CliResult<()> {
             )));
         }
 
-        println!();
-        println!(
-            "{}",
-            ctx.output_format
-                .success(&format!("Theme '{}' installed", theme_id))
-        );
+        human!();
+        ctx.success(&format!("Theme '{}' installed", theme_id));
     } else {
         // URL-based install
         let client = ctx.http_client();
@@ -331,8 +318,8 @@ async fn install_theme(ctx: &CliContext, source: &str) -> CliResult<()> {
             )));
         }
 
-        println!();
-        println!("{}", ctx.output_format.success("Theme installed"));
+        human!();
+        ctx.success("Theme installed");
     }
 
     Ok(())
@@ -361,10 +348,10 @@ async fn delete_theme(ctx: &CliContext, theme: &str, force: bool) -> CliResult<(
     }
 
     if !force {
-        println!("This will permanently delete the theme files.");
-        println!("Run with --force to confirm.");
+        ctx.warning("This will permanently delete the theme files. Run with --force to confirm.");
         return Ok(());
     }
+    ctx.confirm_remote(&format!("delete theme '{}'", theme))?;
 
     print_header("Deleting Theme");
 
@@ -390,11 +377,7 @@ async fn delete_theme(ctx: &CliContext, theme: &str, force: bool) -> CliResult<(
         )));
     }
 
-    println!(
-        "{}",
-        ctx.output_format
-            .success(&format!("Theme '{}' deleted", theme))
-    );
+    ctx.success(&format!("Theme '{}' deleted", theme));
     Ok(())
 }
 
@@ -443,12 +426,9 @@ async fn export_theme(
         if status == reqwest::StatusCode::NOT_FOUND
             || status == reqwest::StatusCode::NOT_IMPLEMENTED
         {
-            println!();
-            println!(
-                "{}",
-                ctx.output_format.info(&format!(
-                    "Theme export not available via API.\nTo export manually, archive the theme directory."
-                ))
+            human!();
+            ctx.info(
+                "Theme export not available via API.\nTo export manually, archive the theme directory.",
             );
             return Ok(());
         }
@@ -468,12 +448,8 @@ async fn export_theme(
 
     std::fs::write(&output_file, &bytes)?;
 
-    println!();
-    println!(
-        "{}",
-        ctx.output_format
-            .success(&format!("Theme exported to {}", output_file))
-    );
+    human!();
+    ctx.success(&format!("Theme exported to {}", output_file));
 
     Ok(())
 }
@@ -515,14 +491,11 @@ async fn scan_themes(ctx: &CliContext) -> CliResult<()> {
         .and_then(|v| v.as_i64())
         .unwrap_or(0);
 
-    println!();
-    println!(
-        "{}",
-        ctx.output_format.success(&format!(
-            "Found {} theme(s), registered {} new theme(s)",
-            found, registered
-        ))
-    );
+    human!();
+    ctx.success(&format!(
+        "Found {} theme(s), registered {} new theme(s)",
+        found, registered
+    ));
 
     Ok(())
 }
@@ -2,11 +2,14 @@
 
 use clap::{Args, Subcommand};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 use tabled::Tabled;
 
 use crate::context::CliContext;
 use crate::error::{CliError, CliResult};
-use crate::output::{print_header, print_kv, OutputFormatter, ProgressBar};
+use crate::output::{print_header, print_kv, ProgressBar};
 
 #[derive(Args, Debug)]
 pub struct UsersCommand {
@@ -116,6 +119,51 @@ pub enum UsersSubcommand {
         #[arg(long)]
         generate: bool,
     },
+
+    /// Import users from a CSV or JSON file
+    Import {
+        /// File to import; CSV needs a header row with an email column
+        file: PathBuf,
+
+        /// File format (csv or json; detected from the extension by default)
+        #[arg(short, long)]
+        format: Option<String>,
+
+        /// Map a role in the file to a site role (repeatable), e.g. member=subscriber
+        #[arg(long = "role-map", value_name = "FROM=TO")]
+        role_map: Vec<String>,
+
+        /// Role of users without one in the file
+        #[arg(long, default_value = "subscriber")]
+        default_role: String,
+
+        /// Email new users a link to set their password
+        #[arg(long)]
+        invite: bool,
+
+        /// Update users whose email already exists instead of skipping them
+        #[arg(long)]
+        update_existing: bool,
+
+        /// Validate and count without creating or changing users
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Start the import and return without waiting for it to finish
+        #[arg(long)]
+        no_wait: bool,
+    },
+
+    /// Export users (without credentials) to CSV or JSON lines
+    Export {
+        /// Export format (csv or jsonl)
+        #[arg(short, long, default_value = "csv")]
+        format: String,
+
+        /// Output file (defaults to stdout)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
 }
 
 #[derive(Debug, Serialize, Deserialize, Tabled)]
@@ -153,6 +201,47 @@ struct ResetPasswordRequest {
     password: String,
 }
 
+#[derive(Debug, Serialize)]
+struct ImportRequest {
+    format: String,
+    role_map: HashMap<String, String>,
+    default_role: String,
+    on_duplicate: &'static str,
+    invite: bool,
+    dry_run: bool,
+    data: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiEnvelope<T> {
+    data: T,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ImportRowError {
+    row: usize,
+    email: String,
+    message: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ImportRun {
+    id: String,
+    status: String,
+    dry_run: bool,
+    total: u64,
+    processed: u64,
+    created: u64,
+    updated: u64,
+    skipped: u64,
+    failed: u64,
+    invited: u64,
+    errors: Vec<ImportRowError>,
+}
+
+/// How often a running import is polled
+const IMPORT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
 #[derive(Debug, Serialize, Deserialize)]
 struct ApiResponse<T> {
     data: Option<T>,
@@ -191,6 +280,30 @@ pub async fn execute(ctx: &CliContext, cmd: UsersCommand) -> CliResult<()> {
             password,
             generate,
         } => reset_password(ctx, &user, password, generate).await,
+        UsersSubcommand::Import {
+            file,
+            format,
+            role_map,
+            default_role,
+            invite,
+            update_existing,
+            dry_run,
+            no_wait,
+        } => {
+            let request = ImportRequest {
+                format: import_format(&file, format.as_deref())?,
+                role_map: parse_role_map(&role_map)?,
+                default_role,
+                on_duplicate: if update_existing { "update" } else { "skip" },
+                invite,
+                dry_run,
+                data: std::fs::read_to_string(&file)?,
+            };
+            import_users(ctx, request, no_wait).await
+        }
+        UsersSubcommand::Export { format, output } => {
+            export_users(ctx, &format, output.as_deref()).await
+        }
     }
 }
 
@@ -242,11 +355,7 @@ async fn list_users(
         .await
         .map_err(|e| CliError::Serialization(format!("Failed to parse response: {}", e)))?;
 
-    if users.is_empty() {
-        println!("{}", ctx.output_format.info("No users found"));
-    } else {
-        println!("{}", ctx.output_format.format(&users));
-    }
+    ctx.list(&users, "No users found");
 
     Ok(())
 }
@@ -291,11 +400,7 @@ async fn create_user(
         )));
     }
 
-    println!(
-        "{}",
-        ctx.output_format
-            .success(&format!("Created user: {} ({})", email, role))
-    );
+    ctx.success(&format!("Created user: {} ({})", email, role));
     Ok(())
 }
 
@@ -385,20 +490,19 @@ async fn update_user(
         )));
     }
 
-    println!(
-        "{}",
-        ctx.output_format
-            .success(&format!("Updated user: {}", user))
-    );
+    ctx.success(&format!("Updated user: {}", user));
     Ok(())
 }
 
 async fn delete_user(ctx: &CliContext, user: &str, force: bool) -> CliResult<()> {
     if !force {
-        println!("This will permanently delete user: {}", user);
-        println!("Run with --force to confirm deletion.");
+        ctx.warning(&format!(
+            "This will permanently delete user: {}. Run with --force to confirm deletion.",
+            user
+        ));
         return Ok(());
     }
+    ctx.confirm_remote(&format!("delete user '{}'", user))?;
 
     print_header("Deleting User");
 
@@ -425,11 +529,7 @@ async fn delete_user(ctx: &CliContext, user: &str, force: bool) -> CliResult<()>
         )));
     }
 
-    println!(
-        "{}",
-        ctx.output_format
-            .success(&format!("Deleted user: {}", user))
-    );
+    ctx.success(&format!("Deleted user: {}", user));
     Ok(())
 }
 
@@ -453,7 +553,7 @@ async fn reset_password(
                 CHARSET[idx] as char
             })
             .collect();
-        println!("Generated password: {}", password);
+        print_kv("Generated password", &password);
         password
     } else {
         password.ok_or_else(|| {
@@ -489,10 +589,254 @@ async fn reset_password(
         )));
     }
 
-    println!(
-        "{}",
-        ctx.output_format
-            .success(&format!("Password reset for user: {}", user))
+    ctx.success(&format!("Password reset for user: {}", user));
+    Ok(())
+}
+
+/// Import format from `--format`, or else the file extension
+fn import_format(file: &Path, format: Option<&str>) -> CliResult<String> {
+    let format = match format {
+        Some(format) => format.to_lowercase(),
+        None => match file.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("json") || ext.eq_ignore_ascii_case("jsonl") => {
+                "json".to_string()
+            }
+            _ => "csv".to_string(),
+        },
+    };
+    match format.as_str() {
+        "csv" => Ok(format),
+        "json" | "jsonl" => Ok("json".to_string()),
+        other => Err(CliError::InvalidInput(format!(
+            "Unknown import format '{}'. Use csv or json",
+            other
+        ))),
+    }
+}
+
+/// Parse repeated `--role-map from=to` options
+fn parse_role_map(pairs: &[String]) -> CliResult<HashMap<String, String>> {
+    pairs
+        .iter()
+        .map(|pair| match pair.split_once('=') {
+            Some((from, to)) if !from.trim().is_empty() && !to.trim().is_empty() => {
+                Ok((from.trim().to_string(), to.trim().to_string()))
+            }
+            _ => Err(CliError::InvalidInput(format!(
+                "Invalid role mapping '{}'. Use FROM=TO, e.g. member=subscriber",
+                pair
+            ))),
+        })
+        .collect()
+}
+
+async fn import_users(ctx: &CliContext, request: ImportRequest, no_wait: bool) -> CliResult<()> {
+    if !request.dry_run {
+        ctx.confirm_remote("import users")?;
+    }
+
+    print_header(if request.dry_run {
+        "Importing Users (dry run)"
+    } else {
+        "Importing Users"
+    });
+
+    let client = api_client(ctx)?;
+    let url = format!("{}/api/v1/users/imports", ctx.server_url());
+
+    let spinner = ProgressBar::spinner("Uploading...");
+    let response = client
+        .post(&url)
+        .header("Authorization", auth_header(ctx)?)
+        .json(&request)
+        .send()
+        .await
+        .map_err(|e| CliError::Network(format!("Failed to start import: {}", e)))?;
+    spinner.finish_and_clear();
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(CliError::OperationFailed(format!(
+            "Failed to start import ({}): {}",
+            status, body
+        )));
+    }
+
+    let mut run = response
+        .json::<ApiEnvelope<ImportRun>>()
+        .await
+        .map_err(|e| CliError::Serialization(format!("Failed to parse response: {}", e)))?
+        .data;
+
+    if no_wait {
+        print_kv("Import", &run.id);
+        print_kv("Rows", &run.total.to_string());
+        ctx.info(&format!(
+            "Import started; follow it at /api/v1/users/imports/{}",
+            run.id
+        ));
+        return Ok(());
+    }
+
+    let progress = ProgressBar::new(run.total, "Importing users");
+    let mut shown = 0;
+    loop {
+        progress.inc(run.processed.saturating_sub(shown));
+        shown = shown.max(run.processed);
+        if run.status != "running" {
+            break;
+        }
+
+        tokio::time::sleep(IMPORT_POLL_INTERVAL).await;
+        let response = client
+            .get(format!("{}/{}", url, run.id))
+            .header("Authorization", auth_header(ctx)?)
+            .send()
+            .await
+            .map_err(|e| CliError::Network(format!("Failed to fetch import progress: {}", e)))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(CliError::OperationFailed(format!(
+                "Failed to fetch import progress ({}): {}",
+                status, body
+            )));
+        }
+        run = response
+            .json::<ApiEnvelope<ImportRun>>()
+            .await
+            .map_err(|e| CliError::Serialization(format!("Failed to parse response: {}", e)))?
+            .data;
+    }
+    progress.finish_and_clear();
+
+    print_kv("Import", &run.id);
+    print_kv("Rows", &run.total.to_string());
+    print_kv("Created", &run.created.to_string());
+    print_kv("Updated", &run.updated.to_string());
+    print_kv("Skipped", &run.skipped.to_string());
+    print_kv("Invited", &run.invited.to_string());
+    print_kv("Failed", &run.failed.to_string());
+    for error in &run.errors {
+        ctx.warning(&format!(
+            "Row {} ({}): {}",
+            error.row, error.email, error.message
+        ));
+    }
+    if run.errors.len() < run.failed as usize {
+        ctx.warning(&format!(
+            "{} more row(s) failed",
+            run.failed as usize - run.errors.len()
+        ));
+    }
+
+    let summary = format!(
+        "{} {} user(s), updated {}, skipped {}",
+        if run.dry_run {
+            "Would create"
+        } else {
+            "Created"
+        },
+        run.created,
+        run.updated,
+        run.skipped
     );
+    if run.status != "completed" {
+        return Err(CliError::OperationFailed(format!(
+            "Import {}: {}",
+            run.status, summary
+        )));
+    }
+    if run.failed > 0 {
+        return Err(CliError::PartialFailure(format!(
+            "{}, {} failed",
+            summary, run.failed
+        )));
+    }
+    ctx.success(&summary);
+
+    Ok(())
+}
+
+async fn export_users(ctx: &CliContext, format: &str, output: Option<&Path>) -> CliResult<()> {
+    if !matches!(format, "csv" | "jsonl") {
+        return Err(CliError::InvalidInput(format!(
+            "Unknown export format '{}'. Use csv or jsonl",
+            format
+        )));
+    }
+
+    let spinner = ProgressBar::spinner("Exporting users...");
+
+    let client = api_client(ctx)?;
+    let url = format!("{}/api/v1/users/export?format={}", ctx.server_url(), format);
+
+    let response = client
+        .get(&url)
+        .header("Authorization", auth_header(ctx)?)
+        .send()
+        .await
+        .map_err(|e| CliError::Network(format!("Failed to export users: {}", e)))?;
+
+    if !response.status().is_success() {
+        spinner.finish_and_clear();
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(CliError::OperationFailed(format!(
+            "Failed to export users ({}): {}",
+            status, body
+        )));
+    }
+
+    let body = response
+        .bytes()
+        .await
+        .map_err(|e| CliError::Network(format!("Failed to download export: {}", e)))?;
+    spinner.finish_and_clear();
+
+    match output {
+        Some(path) => {
+            std::fs::write(path, &body)?;
+            ctx.success(&format!("Exported users to {}", path.display()));
+        }
+        None => {
+            use std::io::Write;
+            std::io::stdout().write_all(&body)?;
+        }
+    }
+
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import_format() {
+        assert_eq!(import_format(Path::new("users.csv"), None).unwrap(), "csv");
+        assert_eq!(
+            import_format(Path::new("users.JSONL"), None).unwrap(),
+            "json"
+        );
+        assert_eq!(
+            import_format(Path::new("users.txt"), Some("jsonl")).unwrap(),
+            "json"
+        );
+        assert!(import_format(Path::new("users.csv"), Some("xml")).is_err());
+    }
+
+    #[test]
+    fn test_parse_role_map() {
+        let map = parse_role_map(&[
+            "member=subscriber".to_string(),
+            " Staff = editor ".to_string(),
+        ])
+        .unwrap();
+        assert_eq!(map["member"], "subscriber");
+        assert_eq!(map["Staff"], "editor");
+        assert!(parse_role_map(&["member".to_string()]).is_err());
+        assert!(parse_role_map(&["=editor".to_string()]).is_err());
+    }
+}
@@ -1,9 +1,12 @@
 //! CLI Context - Holds configuration and state for CLI operations
 
-use crate::commands::Cli;
+use crate::commands::{Cli, RemoteAccess};
 use crate::error::{CliError, CliResult};
-use crate::output::OutputFormat;
+use crate::output::{self, MessageLevel, OutputFormat, OutputFormatter};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::IsTerminal;
+use tabled::Tabled;
 
 /// Stored CLI credentials for authentication
 #[derive(Debug, Serialize, Deserialize, Default, Clone)]
@@ -55,6 +58,97 @@ impl CliCredentials {
     }
 }
 
+/// Live site a command runs against with `--remote`
+#[derive(Debug, Clone)]
+pub struct RemoteTarget {
+    /// Base URL of the site, without a trailing slash
+    pub url: String,
+    /// Host shown in confirmation prompts
+    pub host: String,
+}
+
+impl RemoteTarget {
+    /// Parse the `--remote` URL; only http and https are accepted
+    pub fn parse(remote: &str) -> CliResult<Self> {
+        let url = url::Url::parse(remote.trim())
+            .map_err(|e| CliError::InvalidInput(format!("Invalid --remote URL: {}", e)))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(CliError::InvalidInput(
+                "--remote must be an http:// or https:// URL".to_string(),
+            ));
+        }
+        let host = url
+            .host_str()
+            .ok_or_else(|| CliError::InvalidInput("--remote URL has no host".to_string()))?;
+        let host = match url.port() {
+            Some(port) => format!("{}:{}", host, port),
+            None => host.to_string(),
+        };
+
+        Ok(Self {
+            url: url.as_str().trim_end_matches('/').to_string(),
+            host,
+        })
+    }
+}
+
+/// Access to one API resource
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub struct ResourceAccess {
+    pub read: bool,
+    pub write: bool,
+}
+
+/// What the credentials in use may do on the server, as it reports them
+#[derive(Debug, Clone, Deserialize)]
+pub struct Capabilities {
+    pub server_version: String,
+    #[serde(default)]
+    pub email: Option<String>,
+    #[serde(default)]
+    pub roles: Vec<String>,
+    /// `api_key` or `session`
+    pub auth: String,
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    #[serde(default)]
+    pub resources: BTreeMap<String, ResourceAccess>,
+}
+
+impl Capabilities {
+    /// Check that `access` is granted; resources the server does not list
+    /// are not available there
+    pub fn check(&self, access: RemoteAccess) -> CliResult<()> {
+        let (resource, write) = match access {
+            RemoteAccess::None | RemoteAccess::LocalOnly(_) => return Ok(()),
+            RemoteAccess::Read(resource) => (resource, false),
+            RemoteAccess::Write(resource) => (resource, true),
+        };
+        let granted = self.resources.get(resource).ok_or_else(|| {
+            CliError::NotAvailable(format!(
+                "the server (version {}) does not offer '{}'",
+                self.server_version, resource
+            ))
+        })?;
+
+        if (write && granted.write) || (!write && granted.read) {
+            Ok(())
+        } else {
+            Err(CliError::PermissionDenied(format!(
+                "these credentials may not {} '{}' (scopes: {})",
+                if write { "change" } else { "read" },
+                resource,
+                self.scopes.join(", ")
+            )))
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiEnvelope<T> {
+    data: T,
+}
+
 /// CLI context containing configuration and shared state
 pub struct CliContext {
     /// Output format (table, json, yaml)
@@ -65,6 +159,12 @@ pub struct CliContext {
     pub verbose: u8,
     /// Disable colored output
     pub no_color: bool,
+    /// Live site given with `--remote`, if any
+    remote: Option<RemoteTarget>,
+    /// API key given with `--api-key`, used instead of the stored token
+    api_key: Option<String>,
+    /// Skip confirmation prompts for destructive remote operations
+    assume_yes: bool,
     /// Stored credentials
     credentials: CliCredentials,
 }
@@ -78,22 +178,39 @@ impl CliContext {
         if no_color {
             colored::control::set_override(false);
         }
+        output::configure(cli.output, cli.quiet);
 
         // Load stored credentials
         let credentials = CliCredentials::load();
 
+        let remote = cli.remote.as_deref().map(RemoteTarget::parse).transpose()?;
+        let api_key = cli
+            .api_key
+            .as_deref()
+            .map(str::trim)
+            .filter(|key| !key.is_empty())
+            .map(str::to_string);
+
         Ok(Self {
-            output_format: cli.output.clone(),
+            output_format: cli.output,
             quiet: cli.quiet,
             verbose: cli.verbose,
             no_color,
+            remote,
+            api_key,
+            assume_yes: cli.assume_yes,
             credentials,
         })
     }
 
     /// Check if user is authenticated
     pub fn is_authenticated(&self) -> bool {
-        self.credentials.is_authenticated()
+        self.access_token().is_some()
+    }
+
+    /// Whether commands run against a live site given with `--remote`
+    pub fn is_remote(&self) -> bool {
+        self.remote.is_some()
     }
 
     /// Get the current credentials
@@ -101,14 +218,23 @@ impl CliContext {
         &self.credentials
     }
 
-    /// Get the access token if authenticated
+    /// Get the access token if authenticated: the `--api-key` if given,
+    /// else the stored token, which is only sent to the server it came from
     pub fn access_token(&self) -> Option<&str> {
-        self.credentials.access_token.as_deref()
+        if let Some(key) = &self.api_key {
+            return Some(key);
+        }
+        match &self.remote {
+            Some(remote) if remote.url != self.credentials.server_url.trim_end_matches('/') => None,
+            _ => self.credentials.access_token.as_deref(),
+        }
     }
 
     /// Get the server URL
     pub fn server_url(&self) -> &str {
-        if self.credentials.server_url.is_empty() {
+        if let Some(remote) = &self.remote {
+            &remote.url
+        } else if self.credentials.server_url.is_empty() {
             "http://localhost:3080"
         } else {
             &self.credentials.server_url
@@ -118,40 +244,199 @@ impl CliContext {
     /// Require authentication - returns error if not logged in
     pub fn require_auth(&self) -> CliResult<&str> {
         self.access_token().ok_or_else(|| {
-            CliError::Auth(
-                "Not authenticated. Please run 'rustpress auth login' first.".to_string(),
-            )
+            if self.is_remote() {
+                CliError::Auth(
+                    "Not authenticated with the remote site. Pass --api-key or set RUSTPRESS_API_KEY."
+                        .to_string(),
+                )
+            } else {
+                CliError::Auth(
+                    "Not authenticated. Please run 'rustpress auth login' first.".to_string(),
+                )
+            }
         })
     }
 
-    /// Print a message if not in quiet mode
+    /// Ask the server what the credentials in use may do
+    pub async fn capabilities(&self) -> CliResult<Capabilities> {
+        let url = format!("{}/api/v1/auth/capabilities", self.server_url());
+        let response = self
+            .http_client()
+            .get(&url)
+            .header("Authorization", self.auth_header()?)
+            .send()
+            .await
+            .map_err(|e| {
+                CliError::Network(format!("Could not reach {}: {}", self.server_url(), e))
+            })?;
+
+        match response.status() {
+            status if status.is_success() => response
+                .json::<ApiEnvelope<Capabilities>>()
+                .await
+                .map(|envelope| envelope.data)
+                .map_err(|e| {
+                    CliError::Serialization(format!("Failed to parse server capabilities: {}", e))
+                }),
+            reqwest::StatusCode::UNAUTHORIZED => Err(CliError::Auth(
+                "The server rejected the credentials: the API key is invalid, expired or revoked"
+                    .to_string(),
+            )),
+            reqwest::StatusCode::NOT_FOUND => Err(CliError::NotAvailable(
+                "the server is too old for remote mode (no capabilities endpoint)".to_string(),
+            )),
+            status => Err(CliError::OperationFailed(format!(
+                "Failed to load server capabilities ({})",
+                status
+            ))),
+        }
+    }
+
+    /// In remote mode, check that the command can run there and that the
+    /// credentials allow it
+    pub async fn check_remote_access(&self, access: RemoteAccess) -> CliResult<()> {
+        let Some(remote) = &self.remote else {
+            return Ok(());
+        };
+        match access {
+            RemoteAccess::None => Ok(()),
+            RemoteAccess::LocalOnly(what) => Err(CliError::NotAvailable(format!(
+                "{} works on the local installation and cannot run against {}",
+                what, remote.host
+            ))),
+            _ => {
+                let capabilities = self.capabilities().await?;
+                self.print_verbose(&format!(
+                    "Connected to {} (RustPress {}) as {}",
+                    remote.host,
+                    capabilities.server_version,
+                    capabilities.email.as_deref().unwrap_or("unknown user")
+                ));
+                capabilities.check(access)
+            }
+        }
+    }
+
+    /// Confirm a destructive operation before it runs against a remote
+    /// site. Prompts on a terminal; elsewhere, and in machine-readable
+    /// modes, `--assume-yes` is required. Does nothing locally.
+    pub fn confirm_remote(&self, action: &str) -> CliResult<()> {
+        let Some(remote) = &self.remote else {
+            return Ok(());
+        };
+        if self.assume_yes {
+            return Ok(());
+        }
+        if self.output_format.is_machine_readable() || !std::io::stdin().is_terminal() {
+            return Err(CliError::InvalidInput(format!(
+                "Refusing to {} on {} without confirmation; pass --assume-yes",
+                action, remote.host
+            )));
+        }
+
+        let prompt = format!("{} on {}?", capitalize(action), remote.host);
+        if crate::prompts::confirm_action(&prompt)? {
+            Ok(())
+        } else {
+            Err(CliError::OperationFailed("Cancelled".to_string()))
+        }
+    }
+
+    /// Print a message meant for people, unless in quiet mode or writing
+    /// machine-readable output
     pub fn print(&self, msg: &str) {
-        if !self.quiet {
+        if output::is_decorated() {
             println!("{}", msg);
         }
     }
 
     /// Print a message at verbose level 1+
     pub fn print_verbose(&self, msg: &str) {
-        if self.verbose >= 1 && !self.quiet {
+        if self.verbose >= 1 && output::is_decorated() {
             println!("{}", msg);
         }
     }
 
     /// Print a message at verbose level 2+
     pub fn print_debug(&self, msg: &str) {
-        if self.verbose >= 2 && !self.quiet {
+        if self.verbose >= 2 && output::is_decorated() {
             println!("{}", msg);
         }
     }
 
     /// Print a message at verbose level 3
     pub fn print_trace(&self, msg: &str) {
-        if self.verbose >= 3 && !self.quiet {
+        if self.verbose >= 3 && output::is_decorated() {
             println!("{}", msg);
         }
     }
 
+    /// Output a list of results; an empty list shows `empty` to people and
+    /// stays an empty list in machine-readable output
+    pub fn list<T: Serialize + Tabled>(&self, rows: &[T], empty: &str) {
+        if self.output_format.is_machine_readable() {
+            output::emit_data(rows);
+        } else if rows.is_empty() {
+            self.info(empty);
+        } else {
+            println!("{}", self.output_format.format(rows));
+        }
+    }
+
+    /// Output a single result
+    pub fn item<T: Serialize>(&self, item: &T) {
+        if self.output_format.is_machine_readable() {
+            output::emit_data(item);
+        } else {
+            println!("{}", self.output_format.format_one(item));
+        }
+    }
+
+    /// Output a plain text result, such as a setting value
+    pub fn text(&self, text: &str) {
+        if self.output_format.is_machine_readable() {
+            output::emit_data(text);
+        } else {
+            println!("{}", text);
+        }
+    }
+
+    /// Report that the command did what it was asked
+    pub fn success(&self, msg: &str) {
+        self.message(MessageLevel::Success, msg);
+    }
+
+    /// Report something worth knowing that is not a result
+    pub fn info(&self, msg: &str) {
+        self.message(MessageLevel::Info, msg);
+    }
+
+    /// Report a problem that did not stop the command; shown even in
+    /// quiet mode
+    pub fn warning(&self, msg: &str) {
+        self.message(MessageLevel::Warning, msg);
+    }
+
+    /// Report an error that did not stop the command; shown even in quiet
+    /// mode
+    pub fn error(&self, msg: &str) {
+        self.message(MessageLevel::Error, msg);
+    }
+
+    fn message(&self, level: MessageLevel, msg: &str) {
+        if self.output_format.is_machine_readable() {
+            output::emit_message(level, msg);
+            return;
+        }
+        match level {
+            MessageLevel::Success | MessageLevel::Info if self.quiet => {}
+            MessageLevel::Success => println!("{}", self.output_format.success(msg)),
+            MessageLevel::Info => println!("{}", self.output_format.info(msg)),
+            MessageLevel::Warning => eprintln!("{}", self.output_format.warning(msg)),
+            MessageLevel::Error => eprintln!("{}", self.output_format.error(msg)),
+        }
+    }
+
     /// Check if verbose output is enabled
     pub fn is_verbose(&self) -> bool {
         self.verbose > 0
@@ -168,3 +453,55 @@ impl CliContext {
         Ok(format!("Bearer {}", token))
     }
 }
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remote_target() {
+        let remote = RemoteTarget::parse("https://example.com/").unwrap();
+        assert_eq!(remote.url, "https://example.com");
+        assert_eq!(remote.host, "example.com");
+
+        let remote = RemoteTarget::parse("http://localhost:3080").unwrap();
+        assert_eq!(remote.host, "localhost:3080");
+
+        assert!(RemoteTarget::parse("ftp://example.com").is_err());
+        assert!(RemoteTarget::parse("example.com").is_err());
+    }
+
+    #[test]
+    fn test_capabilities_check() {
+        let capabilities: Capabilities = serde_json::from_value(serde_json::json!({
+            "server_version": "1.0.0",
+            "auth": "api_key",
+            "scopes": ["posts:*", "*:read"],
+            "resources": {
+                "posts": {"read": true, "write": true},
+                "users": {"read": true, "write": false},
+            },
+        }))
+        .unwrap();
+
+        assert!(capabilities.check(RemoteAccess::Write("posts")).is_ok());
+        assert!(capabilities.check(RemoteAccess::Read("users")).is_ok());
+        assert!(matches!(
+            capabilities.check(RemoteAccess::Write("users")),
+            Err(CliError::PermissionDenied(_))
+        ));
+        assert!(matches!(
+            capabilities.check(RemoteAccess::Read("cron")),
+            Err(CliError::NotAvailable(_))
+        ));
+        assert!(capabilities.check(RemoteAccess::None).is_ok());
+    }
+}
@@ -1,4 +1,17 @@
 //! CLI Error types and handling
+//!
+//! Every error maps to an [`ErrorKind`], which fixes the process exit code
+//! scripts can rely on:
+//!
+//! | Code | Kind              | Meaning                                              |
+//! |------|-------------------|------------------------------------------------------|
+//! | 0    |                   | Success                                              |
+//! | 1    | `runtime`         | The operation failed while running                   |
+//! | 2    | `validation`      | Bad arguments, input or configuration; nothing ran   |
+//! | 3    | `partial_failure` | A batch finished but some of its items failed        |
+//! | 4    | `auth`            | Not logged in, or not allowed to do this             |
+//! | 5    | `not_found`       | The named resource does not exist                    |
+//! | 6    | `network`         | The server could not be reached                      |
 
 use thiserror::Error;
 
@@ -41,12 +54,93 @@ pub enum CliError {
     #[error("Feature not available: {0}")]
     NotAvailable(String),
 
+    #[error("Partially failed: {0}")]
+    PartialFailure(String),
+
     #[error("{0}")]
     Other(#[from] anyhow::Error),
 }
 
+/// Class of failure, reported as `error.kind` and as the exit code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    Runtime,
+    Validation,
+    PartialFailure,
+    Auth,
+    NotFound,
+    Network,
+}
+
+impl ErrorKind {
+    /// Name used in machine-readable output
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorKind::Runtime => "runtime",
+            ErrorKind::Validation => "validation",
+            ErrorKind::PartialFailure => "partial_failure",
+            ErrorKind::Auth => "auth",
+            ErrorKind::NotFound => "not_found",
+            ErrorKind::Network => "network",
+        }
+    }
+
+    /// Process exit code
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ErrorKind::Runtime => 1,
+            ErrorKind::Validation => 2,
+            ErrorKind::PartialFailure => 3,
+            ErrorKind::Auth => 4,
+            ErrorKind::NotFound => 5,
+            ErrorKind::Network => 6,
+        }
+    }
+}
+
+impl CliError {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            CliError::Config(_) | CliError::InvalidInput(_) => ErrorKind::Validation,
+            CliError::Auth(_) | CliError::PermissionDenied(_) => ErrorKind::Auth,
+            CliError::NotFound(_) => ErrorKind::NotFound,
+            CliError::Network(_) => ErrorKind::Network,
+            CliError::PartialFailure(_) => ErrorKind::PartialFailure,
+            CliError::Database(_)
+            | CliError::Io(_)
+            | CliError::Serialization(_)
+            | CliError::OperationFailed(_)
+            | CliError::NotAvailable(_)
+            | CliError::Other(_) => ErrorKind::Runtime,
+        }
+    }
+
+    /// Process exit code for this error
+    pub fn exit_code(&self) -> i32 {
+        self.kind().exit_code()
+    }
+}
+
 impl From<serde_json::Error> for CliError {
     fn from(err: serde_json::Error) -> Self {
         CliError::Serialization(err.to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_codes() {
+        let code = |e: CliError| e.exit_code();
+        assert_eq!(code(CliError::OperationFailed("x".into())), 1);
+        assert_eq!(code(CliError::InvalidInput("x".into())), 2);
+        assert_eq!(code(CliError::Config("x".into())), 2);
+        assert_eq!(code(CliError::PartialFailure("x".into())), 3);
+        assert_eq!(code(CliError::Auth("x".into())), 4);
+        assert_eq!(code(CliError::NotFound("x".into())), 5);
+        assert_eq!(code(CliError::Network("x".into())), 6);
+        assert_eq!(CliError::Database("x".into()).kind().as_str(), "runtime");
+    }
+}
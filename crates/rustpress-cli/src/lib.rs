@@ -7,6 +7,7 @@ pub mod commands;
 pub mod context;
 pub mod error;
 pub mod output;
+pub mod prompts;
 
 pub use commands::{Cli, Commands};
 pub use context::CliContext;
@@ -17,7 +17,8 @@ mod repl;
 
 use commands::{Cli, Commands};
 use context::CliContext;
-use error::CliResult;
+use error::{CliError, CliResult};
+use output::human;
 
 #[tokio::main]
 async fn main() {
@@ -27,10 +28,12 @@ async fn main() {
     // Parse CLI arguments
     let cli = Cli::parse();
 
-    // Run the CLI
-    if let Err(e) = run(cli).await {
-        eprintln!("{} {}", "Error:".red().bold(), e);
-        std::process::exit(1);
+    // Run the CLI, then write its report or error and exit with the code
+    // for the kind of failure
+    let result = run(cli).await;
+    let exit_code = output::finish(&result);
+    if exit_code != 0 {
+        std::process::exit(exit_code);
     }
 }
 
@@ -75,6 +78,9 @@ async fn run(cli: Cli) -> CliResult<()> {
         }
     }
 
+    // With --remote, make sure the command can run there before it starts
+    ctx.check_remote_access(cli.command.remote_access()).await?;
+
     // Match and execute the command
     match cli.command {
         Commands::Artifacts { command } => {
@@ -95,29 +101,29 @@ async fn run(cli: Cli) -> CliResult<()> {
         Commands::Seo(cmd) => commands::seo::execute(&ctx, cmd).await,
         Commands::Config(cmd) => commands::config::execute(&ctx, cmd).await,
         Commands::Completion(cmd) => commands::completion::execute(cmd).await,
+        Commands::Import(cmd) => commands::import_export::execute_import(&ctx, cmd).await,
         Commands::ImportExport(cmd) => commands::import_export::execute(&ctx, cmd).await,
         Commands::Cron(cmd) => commands::cron::execute(&ctx, cmd).await,
+        Commands::Doctor(cmd) => commands::doctor::execute(&ctx, cmd).await,
         Commands::Interactive => repl::run_repl().await,
-        Commands::Health { detailed } => run_health_check(detailed).await,
-        Commands::Info => run_system_info().await,
+        Commands::Health { detailed } => run_health_check(&ctx, detailed).await,
+        Commands::Info => run_system_info(&ctx).await,
     }
 }
 
 /// Run system health check
-async fn run_health_check(detailed: bool) -> CliResult<()> {
-    use crate::context::CliCredentials;
-    use crate::output::print_header;
+///
+/// Fails with a network error when the server cannot be reached, so scripts
+/// can rely on the exit code alone.
+async fn run_health_check(ctx: &CliContext, detailed: bool) -> CliResult<()> {
+    use crate::output::{print_header, print_kv, print_section};
 
     print_header("System Health Check");
 
-    let creds = CliCredentials::load();
-    let server_url = if creds.server_url.is_empty() {
-        "http://localhost:3080".to_string()
-    } else {
-        creds.server_url.clone()
-    };
+    let server_url = ctx.server_url().to_string();
 
-    println!("  {} Checking server connectivity...", "→".cyan());
+    human!("  {} Checking server connectivity...", "→".cyan());
+    print_kv("Server URL", &server_url);
 
     let client = reqwest::Client::builder()
         .timeout(std::time::Duration::from_secs(10))
@@ -126,159 +132,141 @@ async fn run_health_check(detailed: bool) -> CliResult<()> {
 
     // Check health endpoint
     let health_url = format!("{}/api/v1/health", server_url);
-    match client.get(&health_url).send().await {
+    let mut details = None;
+    let server_error = match client.get(&health_url).send().await {
         Ok(response) if response.status().is_success() => {
-            println!("  {} Server is {}", "✓".green(), "online".green().bold());
+            human!("  {} Server is {}", "✓".green(), "online".green().bold());
+            print_kv("Server", "online");
 
             if detailed {
-                if let Ok(body) = response.json::<serde_json::Value>().await {
-                    if let Some(obj) = body.as_object() {
-                        println!();
-                        println!("  {}", "Health Details:".cyan());
-                        for (key, value) in obj {
-                            println!("    {}: {}", key.green(), value);
-                        }
-                    }
-                }
+                details = response.json::<serde_json::Value>().await.ok();
             }
+            None
         }
         Ok(response) => {
-            println!(
+            human!(
                 "  {} Server returned {}",
                 "✗".red(),
                 response.status().to_string().red()
             );
+            print_kv("Server", "error");
+            Some(format!("Server returned {}", response.status()))
         }
         Err(e) => {
-            println!(
+            human!(
                 "  {} Server is {} ({})",
                 "✗".red(),
                 "unreachable".red().bold(),
                 e
             );
+            print_kv("Server", "unreachable");
+            Some(format!("Server is unreachable: {}", e))
         }
-    }
+    };
 
     // Check authentication status
-    println!();
-    println!("  {} Checking authentication...", "→".cyan());
-    if creds.access_token.is_some() {
-        println!(
+    human!();
+    human!("  {} Checking authentication...", "→".cyan());
+    if let Some(token) = ctx.access_token() {
+        human!(
             "  {} Authentication token {}",
             "✓".green(),
             "present".green()
         );
 
         // Verify token is valid
-        if let Some(token) = &creds.access_token {
-            let me_url = format!("{}/api/v1/users/me", server_url);
-            match client
-                .get(&me_url)
-                .header("Authorization", format!("Bearer {}", token))
-                .send()
-                .await
-            {
-                Ok(response) if response.status().is_success() => {
-                    println!("  {} Token is {}", "✓".green(), "valid".green());
-                }
-                Ok(response) if response.status() == reqwest::StatusCode::UNAUTHORIZED => {
-                    println!("  {} Token is {}", "✗".yellow(), "expired".yellow());
-                    println!(
-                        "    {} Run 'rustpress auth login' to re-authenticate",
-                        "Tip:".dimmed()
-                    );
-                }
-                _ => {
-                    println!("  {} Token validation {}", "?".yellow(), "unknown".yellow());
-                }
+        let me_url = format!("{}/api/v1/users/me", server_url);
+        let token_status = match client
+            .get(&me_url)
+            .header("Authorization", format!("Bearer {}", token))
+            .send()
+            .await
+        {
+            Ok(response) if response.status().is_success() => {
+                human!("  {} Token is {}", "✓".green(), "valid".green());
+                "valid"
             }
-        }
+            Ok(response) if response.status() == reqwest::StatusCode::UNAUTHORIZED => {
+                human!("  {} Token is {}", "✗".yellow(), "expired".yellow());
+                human!(
+                    "    {} Run 'rustpress auth login' to re-authenticate",
+                    "Tip:".dimmed()
+                );
+                "expired"
+            }
+            _ => {
+                human!("  {} Token validation {}", "?".yellow(), "unknown".yellow());
+                "unknown"
+            }
+        };
+        print_kv("Token", token_status);
     } else {
-        println!("  {} Not authenticated", "○".yellow());
+        human!("  {} Not authenticated", "○".yellow());
+        print_kv("Token", "missing");
+    }
+
+    if let Some(obj) = details.as_ref().and_then(|body| body.as_object()) {
+        human!();
+        print_section("Health Details");
+        for (key, value) in obj {
+            print_kv(key, &value.to_string());
+        }
     }
 
-    println!();
-    println!("{}", "Health check complete.".dimmed());
+    if let Some(message) = server_error {
+        return Err(CliError::Network(message));
+    }
+
+    human!();
+    human!("{}", "Health check complete.".dimmed());
 
     Ok(())
 }
 
 /// Run system info command
-async fn run_system_info() -> CliResult<()> {
-    use crate::context::CliCredentials;
-    use crate::output::print_header;
+async fn run_system_info(ctx: &CliContext) -> CliResult<()> {
+    use crate::output::{print_header, print_kv, print_section};
 
     print_header("System Information");
 
-    println!();
-    println!("  {}", "CLI:".cyan().bold());
-    println!("    {} {}", "Version:".green(), env!("CARGO_PKG_VERSION"));
-    println!("    {} {}", "OS:".green(), std::env::consts::OS);
-    println!("    {} {}", "Architecture:".green(), std::env::consts::ARCH);
-
-    let creds = CliCredentials::load();
-    println!();
-    println!("  {}", "Configuration:".cyan().bold());
-    println!(
-        "    {} {}",
-        "Server URL:".green(),
-        if creds.server_url.is_empty() {
-            "http://localhost:3080 (default)"
+    human!();
+    print_section("CLI");
+    print_kv("Version", env!("CARGO_PKG_VERSION"));
+    print_kv("OS", std::env::consts::OS);
+    print_kv("Architecture", std::env::consts::ARCH);
+
+    let creds = ctx.credentials();
+    human!();
+    print_section("Configuration");
+    print_kv("Mode", if ctx.is_remote() { "remote" } else { "local" });
+    print_kv(
+        "Server URL",
+        if ctx.is_remote() || !creds.server_url.is_empty() {
+            ctx.server_url()
         } else {
-            &creds.server_url
-        }
+            "http://localhost:3080 (default)"
+        },
     );
-    println!(
-        "    {} {}",
-        "Authenticated:".green(),
-        if creds.access_token.is_some() {
-            "Yes".green().to_string()
-        } else {
-            "No".yellow().to_string()
-        }
+    print_kv(
+        "Authenticated",
+        if ctx.is_authenticated() { "Yes" } else { "No" },
     );
-    if let Some(email) = &creds.email {
-        println!("    {} {}", "User:".green(), email);
+    if let (false, Some(email)) = (ctx.is_remote(), &creds.email) {
+        print_kv("User", email);
     }
 
-    // Try to get server info
-    if creds.access_token.is_some() {
-        let client = reqwest::Client::builder()
-            .timeout(std::time::Duration::from_secs(5))
-            .build()
-            .unwrap();
-
-        let server_url = if creds.server_url.is_empty() {
-            "http://localhost:3080"
-        } else {
-            &creds.server_url
-        };
-
-        let info_url = format!("{}/api/v1/info", server_url);
-        if let Ok(response) = client
-            .get(&info_url)
-            .header(
-                "Authorization",
-                format!("Bearer {}", creds.access_token.as_ref().unwrap()),
-            )
-            .send()
-            .await
-        {
-            if response.status().is_success() {
-                if let Ok(info) = response.json::<serde_json::Value>().await {
-                    println!();
-                    println!("  {}", "Server:".cyan().bold());
-                    if let Some(obj) = info.as_object() {
-                        for (key, value) in obj {
-                            let display_value = match value {
-                                serde_json::Value::String(s) => s.clone(),
-                                _ => value.to_string(),
-                            };
-                            println!("    {} {}", format!("{}:", key).green(), display_value);
-                        }
-                    }
-                }
+    // Ask the server who we are and what we may do
+    if ctx.is_authenticated() {
+        if let Ok(capabilities) = ctx.capabilities().await {
+            human!();
+            print_section("Server");
+            print_kv("Version", &capabilities.server_version);
+            if let Some(email) = &capabilities.email {
+                print_kv("User", email);
             }
+            print_kv("Roles", &capabilities.roles.join(", "));
+            print_kv("Auth", &capabilities.auth);
+            print_kv("Scopes", &capabilities.scopes.join(", "));
         }
     }
 
@@ -1,15 +1,38 @@
 //! Output formatting for CLI results
+//!
+//! With `--output table` or `--output plain` commands print for people:
+//! headers, key-value lines, spinners and status messages. With `--output
+//! json` or `--output yaml` nothing but a single report is written to
+//! stdout once the command finishes:
+//!
+//! ```json
+//! {
+//!   "status": "ok",
+//!   "exit_code": 0,
+//!   "data": [{ "id": "...", "email": "..." }],
+//!   "messages": [{ "level": "success", "message": "..." }],
+//!   "error": null
+//! }
+//! ```
+//!
+//! `status` is `ok`, `error` or `partial`, `error` holds `kind` and
+//! `message` (see [`ErrorKind`](crate::error::ErrorKind)), and field names
+//! inside `data` are snake_case and do not change with the table headers.
+//! `--quiet` drops headers, spinners and info/success messages but keeps
+//! data, warnings and errors.
 
+use crate::error::{CliResult, ErrorKind};
 use colored::Colorize;
 use serde::Serialize;
 use std::fmt::Display;
+use std::sync::{Mutex, RwLock};
 use tabled::{
     settings::{object::Columns, Modify, Style, Width},
     Table, Tabled,
 };
 
 /// Output format options
-#[derive(Debug, Clone, Default, clap::ValueEnum)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
 pub enum OutputFormat {
     /// Pretty-printed table (default)
     #[default]
@@ -35,6 +58,10 @@ impl Display for OutputFormat {
 
 /// Output formatter trait
 pub trait OutputFormatter {
+    /// Whether output is meant for scripts rather than people
+    fn is_machine_readable(&self) -> bool;
+    /// Serialize a whole report
+    fn render<T: Serialize>(&self, data: &T) -> String;
     /// Format data for output
     fn format<T: Serialize + Tabled>(&self, data: &[T]) -> String;
     /// Format a single item
@@ -50,6 +77,19 @@ pub trait OutputFormatter {
 }
 
 impl OutputFormatter for OutputFormat {
+    fn is_machine_readable(&self) -> bool {
+        matches!(self, OutputFormat::Json | OutputFormat::Yaml)
+    }
+
+    fn render<T: Serialize>(&self, data: &T) -> String {
+        match self {
+            OutputFormat::Yaml => {
+                serde_yaml::to_string(data).unwrap_or_else(|e| format!("Error: {}", e))
+            }
+            _ => serde_json::to_string_pretty(data).unwrap_or_else(|e| format!("Error: {}", e)),
+        }
+    }
+
     fn format<T: Serialize + Tabled>(&self, data: &[T]) -> String {
         match self {
             OutputFormat::Table => {
@@ -98,7 +138,9 @@ impl OutputFormatter for OutputFormat {
             OutputFormat::Json => {
                 serde_json::json!({"status": "success", "message": msg}).to_string()
             }
-            OutputFormat::Yaml => format!("status: success\nmessage: {}", msg),
+            OutputFormat::Yaml => {
+                self.render(&serde_json::json!({"status": "success", "message": msg}))
+            }
             _ => format!("{} {}", "✓".green().bold(), msg.green()),
         }
     }
@@ -108,7 +150,9 @@ impl OutputFormatter for OutputFormat {
             OutputFormat::Json => {
                 serde_json::json!({"status": "error", "message": msg}).to_string()
             }
-            OutputFormat::Yaml => format!("status: error\nmessage: {}", msg),
+            OutputFormat::Yaml => {
+                self.render(&serde_json::json!({"status": "error", "message": msg}))
+            }
             _ => format!("{} {}", "✗".red().bold(), msg.red()),
         }
     }
@@ -118,7 +162,9 @@ impl OutputFormatter for OutputFormat {
             OutputFormat::Json => {
                 serde_json::json!({"status": "warning", "message": msg}).to_string()
             }
-            OutputFormat::Yaml => format!("status: warning\nmessage: {}", msg),
+            OutputFormat::Yaml => {
+                self.render(&serde_json::json!({"status": "warning", "message": msg}))
+            }
             _ => format!("{} {}", "⚠".yellow().bold(), msg.yellow()),
         }
     }
@@ -126,7 +172,9 @@ impl OutputFormatter for OutputFormat {
     fn info(&self, msg: &str) -> String {
         match self {
             OutputFormat::Json => serde_json::json!({"status": "info", "message": msg}).to_string(),
-            OutputFormat::Yaml => format!("status: info\nmessage: {}", msg),
+            OutputFormat::Yaml => {
+                self.render(&serde_json::json!({"status": "info", "message": msg}))
+            }
             _ => format!("{} {}", "ℹ".blue().bold(), msg),
         }
     }
@@ -170,6 +218,203 @@ fn format_plain_value(value: &serde_json::Value) -> String {
     }
 }
 
+/// How the current command writes its output
+#[derive(Debug, Clone, Copy)]
+struct Mode {
+    format: OutputFormat,
+    quiet: bool,
+}
+
+static MODE: RwLock<Mode> = RwLock::new(Mode {
+    format: OutputFormat::Table,
+    quiet: false,
+});
+
+static REPORT: Mutex<Option<Report>> = Mutex::new(None);
+
+/// Set the output mode for the command about to run and start a fresh
+/// report
+pub fn configure(format: OutputFormat, quiet: bool) {
+    *MODE.write().unwrap_or_else(|e| e.into_inner()) = Mode { format, quiet };
+    *report() = None;
+    if format.is_machine_readable() {
+        colored::control::set_override(false);
+    }
+}
+
+fn mode() -> Mode {
+    *MODE.read().unwrap_or_else(|e| e.into_inner())
+}
+
+fn report() -> std::sync::MutexGuard<'static, Option<Report>> {
+    REPORT.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn with_report(f: impl FnOnce(&mut Report)) {
+    f(report().get_or_insert_with(Report::default));
+}
+
+/// Whether output goes into the report rather than to the terminal
+pub fn is_machine_readable() -> bool {
+    mode().format.is_machine_readable()
+}
+
+/// Whether headers, spinners and narration are shown
+pub fn is_decorated() -> bool {
+    let mode = mode();
+    !mode.quiet && !mode.format.is_machine_readable()
+}
+
+/// Whether `--quiet` was given
+pub fn is_quiet() -> bool {
+    mode().quiet
+}
+
+/// Print a line meant for people only; nothing is printed in quiet mode
+/// or with machine-readable output
+macro_rules! human {
+    ($($arg:tt)*) => {
+        if $crate::output::is_decorated() {
+            println!($($arg)*);
+        }
+    };
+}
+pub(crate) use human;
+
+/// Level of a status message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MessageLevel {
+    Success,
+    Info,
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct Message {
+    level: MessageLevel,
+    message: String,
+}
+
+/// Everything a command produced, written once in machine-readable modes
+#[derive(Debug, Default)]
+struct Report {
+    data: Vec<serde_json::Value>,
+    fields: serde_json::Map<String, serde_json::Value>,
+    section: Option<String>,
+    messages: Vec<Message>,
+}
+
+impl Report {
+    fn record_field(&mut self, key: &str, value: &str) {
+        let value = serde_json::Value::String(value.to_string());
+        let fields = match &self.section {
+            Some(section) => match self
+                .fields
+                .entry(section.clone())
+                .or_insert_with(|| serde_json::Value::Object(Default::default()))
+            {
+                serde_json::Value::Object(map) => map,
+                _ => return,
+            },
+            None => &mut self.fields,
+        };
+        fields.insert(field_name(key), value);
+    }
+
+    /// The report for a command that ended with `result`
+    fn into_document(self, result: &CliResult<()>) -> serde_json::Value {
+        let fields = (!self.fields.is_empty()).then_some(serde_json::Value::Object(self.fields));
+        let mut data = self.data;
+        let (data, details) = match data.len() {
+            0 => (fields.unwrap_or(serde_json::Value::Null), None),
+            1 => (data.remove(0), fields),
+            _ => (serde_json::Value::Array(data), fields),
+        };
+
+        let (status, exit_code, error) = match result {
+            Ok(()) => ("ok", 0, serde_json::Value::Null),
+            Err(e) => {
+                let kind = e.kind();
+                let status = if kind == ErrorKind::PartialFailure {
+                    "partial"
+                } else {
+                    "error"
+                };
+                let error = serde_json::json!({
+                    "kind": kind.as_str(),
+                    "message": e.to_string(),
+                });
+                (status, kind.exit_code(), error)
+            }
+        };
+
+        let mut document = serde_json::json!({
+            "status": status,
+            "exit_code": exit_code,
+            "data": data,
+            "messages": self.messages,
+            "error": error,
+        });
+        if let Some(details) = details {
+            document["details"] = details;
+        }
+        document
+    }
+}
+
+/// Record a list or item as the command's result
+pub fn emit_data<T: Serialize + ?Sized>(data: &T) {
+    let value = serde_json::to_value(data).unwrap_or(serde_json::Value::Null);
+    with_report(|report| report.data.push(value));
+}
+
+/// Record a status message
+pub fn emit_message(level: MessageLevel, message: &str) {
+    with_report(|report| {
+        report.messages.push(Message {
+            level,
+            message: message.to_string(),
+        })
+    });
+}
+
+/// Stable snake_case field name for a human label, `"Server URL"` becomes
+/// `server_url`
+pub fn field_name(label: &str) -> String {
+    let mut name = String::with_capacity(label.len());
+    for c in label.trim().trim_end_matches(':').chars() {
+        if c.is_alphanumeric() {
+            name.extend(c.to_lowercase());
+        } else if !name.is_empty() && !name.ends_with('_') {
+            name.push('_');
+        }
+    }
+    name.trim_end_matches('_').to_string()
+}
+
+/// Write the report of a finished command in machine-readable modes, or
+/// the error for people otherwise. Returns the process exit code.
+pub fn finish(result: &CliResult<()>) -> i32 {
+    let exit_code = match result {
+        Ok(()) => 0,
+        Err(e) => e.exit_code(),
+    };
+    let mode = mode();
+
+    if !mode.format.is_machine_readable() {
+        if let Err(e) = result {
+            eprintln!("{} {}", "Error:".red().bold(), e);
+        }
+        return exit_code;
+    }
+
+    let document = report().take().unwrap_or_default().into_document(result);
+    println!("{}", mode.format.render(&document));
+    exit_code
+}
+
 /// Progress bar helper for long-running operations
 pub struct ProgressBar {
     bar: indicatif::ProgressBar,
@@ -187,6 +432,7 @@ impl ProgressBar {
                 .progress_chars("#>-"),
         );
         bar.set_message(message.to_string());
+        Self::hide_unless_decorated(&bar);
         Self { bar }
     }
 
@@ -198,10 +444,18 @@ impl ProgressBar {
                 .unwrap(),
         );
         bar.set_message(message.to_string());
+        Self::hide_unless_decorated(&bar);
         bar.enable_steady_tick(std::time::Duration::from_millis(100));
         Self { bar }
     }
 
+    /// Progress is not drawn in quiet mode or with machine-readable output
+    fn hide_unless_decorated(bar: &indicatif::ProgressBar) {
+        if !is_decorated() {
+            bar.set_draw_target(indicatif::ProgressDrawTarget::hidden());
+        }
+    }
+
     pub fn inc(&self, delta: u64) {
         self.bar.inc(delta);
     }
@@ -219,18 +473,86 @@ impl ProgressBar {
     }
 }
 
-/// Helper to print a section header
+/// Helper to print a section header; in machine-readable modes it starts a
+/// section of the report that following key-value lines go into
 pub fn print_header(title: &str) {
+    if is_machine_readable() {
+        let section = field_name(title);
+        with_report(|report| report.section = (!section.is_empty()).then_some(section));
+        return;
+    }
+    if is_quiet() {
+        return;
+    }
     println!("\n{}", title.bold().underline());
     println!();
 }
 
-/// Helper to print a key-value line
+/// Helper to print a bold sub-heading; like [`print_header`] it starts a
+/// report section in machine-readable modes
+pub fn print_section(title: &str) {
+    if is_machine_readable() {
+        let section = field_name(title);
+        with_report(|report| report.section = (!section.is_empty()).then_some(section));
+        return;
+    }
+    human!("{}", title.bold());
+}
+
+/// Helper to print a key-value line; in machine-readable modes it becomes a
+/// report field named after `key`
 pub fn print_kv(key: &str, value: &str) {
+    if is_machine_readable() {
+        with_report(|report| report.record_field(key, value));
+        return;
+    }
     println!("  {}: {}", key.dimmed(), value);
 }
 
 /// Helper to print a bullet point
 pub fn print_bullet(text: &str) {
-    println!("  {} {}", "•".dimmed(), text);
+    human!("  {} {}", "•".dimmed(), text);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::CliError;
+
+    #[test]
+    fn test_field_name() {
+        assert_eq!(field_name("Server URL"), "server_url");
+        assert_eq!(field_name("  Last Run: "), "last_run");
+        assert_eq!(field_name("Posts (published)"), "posts_published");
+        assert_eq!(field_name("ID"), "id");
+    }
+
+    #[test]
+    fn test_report_document() {
+        let mut report = Report::default();
+        report.record_field("Version", "0.4.0");
+        report.section = Some("database".to_string());
+        report.record_field("Pool Size", "10");
+        report.messages.push(Message {
+            level: MessageLevel::Warning,
+            message: "Cache disabled".to_string(),
+        });
+
+        let document = report.into_document(&Ok(()));
+        assert_eq!(document["status"], "ok");
+        assert_eq!(document["exit_code"], 0);
+        assert_eq!(document["data"]["version"], "0.4.0");
+        assert_eq!(document["data"]["database"]["pool_size"], "10");
+        assert_eq!(document["messages"][0]["level"], "warning");
+        assert!(document["error"].is_null());
+
+        let mut report = Report::default();
+        report.data.push(serde_json::json!([{ "key": "a" }]));
+        let failed: CliResult<()> = Err(CliError::PartialFailure("1 of 2 failed".to_string()));
+        let document = report.into_document(&failed);
+        assert_eq!(document["status"], "partial");
+        assert_eq!(document["exit_code"], 3);
+        assert_eq!(document["error"]["kind"], "partial_failure");
+        assert_eq!(document["data"][0]["key"], "a");
+    }
 }
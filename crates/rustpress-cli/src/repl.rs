@@ -121,6 +121,7 @@ impl ReplCompleter {
                 "config show".into(),
                 "config validate".into(),
                 "config init".into(),
+                "config bundle".into(),
                 // Health check
                 "health".into(),
                 "health check".into(),
@@ -299,9 +300,7 @@ pub async fn run_repl() -> CliResult<()> {
                 }
 
                 // Parse and execute the command
-                if let Err(e) = execute_command(line).await {
-                    eprintln!("{} {}", "Error:".red().bold(), e);
-                }
+                crate::output::finish(&execute_command(line).await);
                 println!();
             }
             Err(ReadlineError::Interrupted) => {
@@ -402,8 +401,10 @@ async fn execute_command(line: &str) -> CliResult<()> {
         Commands::Seo(cmd) => crate::commands::seo::execute(&ctx, cmd).await,
         Commands::Config(cmd) => crate::commands::config::execute(&ctx, cmd).await,
         Commands::Completion(cmd) => crate::commands::completion::execute(cmd).await,
+        Commands::Import(cmd) => crate::commands::import_export::execute_import(&ctx, cmd).await,
         Commands::ImportExport(cmd) => crate::commands::import_export::execute(&ctx, cmd).await,
         Commands::Cron(cmd) => crate::commands::cron::execute(&ctx, cmd).await,
+        Commands::Doctor(cmd) => crate::commands::doctor::execute(&ctx, cmd).await,
         Commands::Interactive => {
             println!("Already in interactive mode!");
             Ok(())
@@ -10,6 +10,7 @@
 //! - Content templates
 //! - Custom post types
 //! - Taxonomy management
+//! - Multi-region site mapping and hreflang
 
 pub mod access;
 pub mod autosave;
@@ -24,6 +25,7 @@ pub mod markdown;
 pub mod media;
 pub mod oembed;
 pub mod post_types;
+pub mod regions;
 pub mod related;
 pub mod revision;
 pub mod sanitize;
@@ -55,6 +57,7 @@ pub use markdown::*;
 pub use media::*;
 pub use oembed::*;
 pub use post_types::*;
+pub use regions::*;
 pub use related::*;
 pub use revision::*;
 pub use sanitize::*;
@@ -97,6 +100,9 @@ pub enum ContentError {
 
     #[error("Scheduler error: {0}")]
     Scheduler(String),
+
+    #[error("Embed error: {0}")]
+    Embed(String),
 }
 
 pub type ContentResult<T> = Result<T, ContentError>;
@@ -9,8 +9,11 @@
 //! - Fallback handling
 //! - Security filtering
 
+use crate::{ContentError, ContentResult};
 use regex::Regex;
+use rustpress_core::http::HttpClient;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::collections::HashMap;
 use url::Url;
 
@@ -87,6 +90,37 @@ pub struct OembedResponse {
 }
 
 impl OembedResponse {
+    /// Parse a provider's JSON response. Providers disagree on whether
+    /// dimensions are numbers or strings, so both are accepted.
+    pub fn from_json(json: &Value) -> Option<Self> {
+        let text = |field: &str| json.get(field).and_then(Value::as_str).map(String::from);
+        let number = |field: &str| {
+            json.get(field).and_then(|value| match value {
+                Value::Number(n) => n.as_u64().and_then(|n| u32::try_from(n).ok()),
+                Value::String(s) => s.parse().ok(),
+                _ => None,
+            })
+        };
+
+        Some(Self {
+            oembed_type: OembedType::from_str(json.get("type")?.as_str()?)?,
+            version: text("version").unwrap_or_else(|| "1.0".to_string()),
+            title: text("title"),
+            author_name: text("author_name"),
+            author_url: text("author_url"),
+            provider_name: text("provider_name"),
+            provider_url: text("provider_url"),
+            cache_age: number("cache_age").map(u64::from),
+            thumbnail_url: text("thumbnail_url"),
+            thumbnail_width: number("thumbnail_width"),
+            thumbnail_height: number("thumbnail_height"),
+            url: text("url"),
+            width: number("width"),
+            height: number("height"),
+            html: text("html"),
+        })
+    }
+
     /// Get responsive embed HTML
     pub fn get_embed_html(&self, max_width: Option<u32>) -> String {
         if let Some(ref html) = self.html {
@@ -393,6 +427,37 @@ impl OembedRegistry {
         );
     }
 
+    /// Embed data for `url`, from the cache while it is fresh or else from
+    /// the provider. `None` when no provider handles the URL.
+    pub async fn fetch(
+        &mut self,
+        client: &HttpClient,
+        url: &str,
+    ) -> ContentResult<Option<OembedResponse>> {
+        if let Some(response) = self.get_cached(url) {
+            return Ok(Some(response.clone()));
+        }
+        let Some(request_url) = self.get_request_url(url) else {
+            return Ok(None);
+        };
+
+        let embed_error = |e: String| ContentError::Embed(format!("{}: {}", url, e));
+        let json: Value = client
+            .send(client.get(&request_url))
+            .await
+            .map_err(|e| embed_error(e.to_string()))?
+            .error_for_status()
+            .map_err(|e| embed_error(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| embed_error(e.to_string()))?;
+        let response = OembedResponse::from_json(&json)
+            .ok_or_else(|| embed_error("invalid oEmbed response".to_string()))?;
+
+        self.cache_response(url, response.clone());
+        Ok(Some(response))
+    }
+
     /// Get embed request URL for provider
     pub fn get_request_url(&self, url: &str) -> Option<String> {
         self.find_provider(url)
@@ -498,6 +563,24 @@ mod tests {
         assert!(url.contains("format=json"));
     }
 
+    #[test]
+    fn test_response_from_json() {
+        let json = serde_json::json!({
+            "type": "video",
+            "version": "1.0",
+            "provider_name": "YouTube",
+            "html": "<iframe></iframe>",
+            "width": 480,
+            "height": "270",
+        });
+        let response = OembedResponse::from_json(&json).unwrap();
+        assert_eq!(response.oembed_type, OembedType::Video);
+        assert_eq!(response.width, Some(480));
+        assert_eq!(response.height, Some(270));
+
+        assert!(OembedResponse::from_json(&serde_json::json!({ "version": "1.0" })).is_none());
+    }
+
     #[test]
     fn test_process_embeds() {
         let registry = OembedRegistry::new();
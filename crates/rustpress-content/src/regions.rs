@@ -0,0 +1,517 @@
+//! Multi-region site mapping and hreflang management.
+//!
+//! Features:
+//! - Region mapping (country → site/language variant)
+//! - Automatic hreflang and x-default link generation
+//! - Geo-aware redirect suggestions (never forced redirects)
+//! - Validation of hreflang reciprocity across crawled pages
+
+use crate::sanitize::escape_attr;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// A regional variant of the site (e.g. "en-GB" served from example.co.uk)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegionVariant {
+    /// Unique variant key (e.g. "uk")
+    pub id: String,
+    /// ISO 639 language code (e.g. "en" or "fil")
+    pub language: String,
+    /// ISO 3166-1 alpha-2 country or UN M.49 area code (e.g. "GB",
+    /// "419"); `None` for language-only variants
+    #[serde(default)]
+    pub country: Option<String>,
+    /// Base URL of the variant (e.g. "https://example.co.uk")
+    pub base_url: String,
+}
+
+impl RegionVariant {
+    pub fn new(id: &str, language: &str, base_url: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            language: language.to_lowercase(),
+            country: None,
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    pub fn for_country(mut self, country: &str) -> Self {
+        self.country = Some(country.to_uppercase());
+        self
+    }
+
+    /// hreflang value for this variant (e.g. "en-GB" or "en")
+    pub fn hreflang(&self) -> String {
+        match &self.country {
+            Some(country) => format!("{}-{}", self.language, country),
+            None => self.language.clone(),
+        }
+    }
+
+    /// Absolute URL for a path on this variant
+    pub fn url_for(&self, path: &str) -> String {
+        format!("{}/{}", self.base_url, path.trim_start_matches('/'))
+    }
+}
+
+/// A single hreflang alternate link
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HreflangLink {
+    /// hreflang value ("en-GB", "fr", "x-default")
+    pub hreflang: String,
+    /// Absolute URL of the alternate
+    pub href: String,
+}
+
+impl HreflangLink {
+    pub fn to_tag(&self) -> String {
+        format!(
+            r#"<link rel="alternate" hreflang="{}" href="{}">"#,
+            escape_attr(&self.hreflang),
+            escape_attr(&self.href)
+        )
+    }
+}
+
+/// A suggestion to switch to another regional variant.
+///
+/// Suggestions are meant to drive a dismissible banner or prompt; visitors
+/// and crawlers are never redirected automatically.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RegionRedirectSuggestion {
+    pub variant_id: String,
+    pub hreflang: String,
+    pub url: String,
+    /// Why the variant was suggested ("country" or "language")
+    pub reason: String,
+}
+
+/// Kind of hreflang problem
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HreflangIssueKind {
+    /// Code is not a valid language(-region) or x-default
+    InvalidCode,
+    /// Two variants or links share the same hreflang code
+    DuplicateCode,
+    /// Mapping references a variant that does not exist
+    UnknownVariant,
+    /// The page does not list itself among its alternates
+    MissingSelfReference,
+    /// An alternate does not link back to the page
+    MissingReturnLink,
+    /// More than one x-default link
+    MultipleXDefault,
+}
+
+/// A detected hreflang or region mapping problem
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HreflangIssue {
+    pub kind: HreflangIssueKind,
+    /// Page URL (or variant ID for config issues)
+    pub subject: String,
+    pub detail: String,
+}
+
+impl HreflangIssue {
+    fn new(kind: HreflangIssueKind, subject: &str, detail: String) -> Self {
+        Self {
+            kind,
+            subject: subject.to_string(),
+            detail,
+        }
+    }
+}
+
+/// Region mapping configuration for a multi-regional site
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RegionMapping {
+    /// Variants keyed by ID
+    #[serde(default)]
+    pub variants: BTreeMap<String, RegionVariant>,
+    /// Country code → variant ID
+    #[serde(default)]
+    pub countries: HashMap<String, String>,
+    /// Variant used for x-default
+    #[serde(default)]
+    pub x_default: Option<String>,
+}
+
+impl RegionMapping {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_variant(mut self, variant: RegionVariant) -> Self {
+        self.variants.insert(variant.id.clone(), variant);
+        self
+    }
+
+    pub fn map_country(mut self, country: &str, variant_id: &str) -> Self {
+        self.countries
+            .insert(country.to_uppercase(), variant_id.to_string());
+        self
+    }
+
+    pub fn with_x_default(mut self, variant_id: &str) -> Self {
+        self.x_default = Some(variant_id.to_string());
+        self
+    }
+
+    /// Variant serving a country, if mapped
+    pub fn variant_for_country(&self, country: &str) -> Option<&RegionVariant> {
+        self.countries
+            .get(&country.to_uppercase())
+            .and_then(|id| self.variants.get(id))
+    }
+
+    /// Build hreflang links for a page.
+    ///
+    /// `alternates` maps variant IDs to the path of the equivalent page on
+    /// that variant; variants without a translation are skipped. x-default
+    /// is emitted when the configured default variant has the page.
+    pub fn hreflang_links(&self, alternates: &HashMap<String, String>) -> Vec<HreflangLink> {
+        let mut links: Vec<HreflangLink> = self
+            .variants
+            .values()
+            .filter_map(|variant| {
+                alternates.get(&variant.id).map(|path| HreflangLink {
+                    hreflang: variant.hreflang(),
+                    href: variant.url_for(path),
+                })
+            })
+            .collect();
+
+        if let Some(default) = self.x_default.as_ref().and_then(|id| self.variants.get(id)) {
+            if let Some(path) = alternates.get(&default.id) {
+                links.push(HreflangLink {
+                    hreflang: "x-default".to_string(),
+                    href: default.url_for(path),
+                });
+            }
+        }
+
+        links
+    }
+
+    /// Render hreflang `<link>` tags for a page
+    pub fn render_hreflang_tags(&self, alternates: &HashMap<String, String>) -> String {
+        self.hreflang_links(alternates)
+            .iter()
+            .map(HreflangLink::to_tag)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Suggest a better-matching variant for a visitor, if any.
+    ///
+    /// Country mapping wins over the `Accept-Language` header. Returns `None`
+    /// when the current variant already matches or the page has no
+    /// equivalent on the suggested variant.
+    pub fn suggest_redirect(
+        &self,
+        current_variant: &str,
+        country: Option<&str>,
+        accept_language: Option<&str>,
+        alternates: &HashMap<String, String>,
+    ) -> Option<RegionRedirectSuggestion> {
+        let by_country = country
+            .and_then(|c| self.variant_for_country(c))
+            .map(|v| (v, "country"));
+        let by_language = || {
+            accept_language
+                .and_then(|header| header.split(',').next())
+                .and_then(|lang| lang.split(';').next())
+                .map(|lang| lang.trim().to_lowercase())
+                .and_then(|lang| {
+                    let primary = lang.split('-').next().unwrap_or_default().to_string();
+                    self.variants
+                        .values()
+                        .find(|v| v.hreflang().to_lowercase() == lang)
+                        .or_else(|| self.variants.values().find(|v| v.language == primary))
+                })
+                .map(|v| (v, "language"))
+        };
+
+        let (variant, reason) = by_country.or_else(by_language)?;
+        if variant.id == current_variant {
+            return None;
+        }
+
+        let path = alternates.get(&variant.id)?;
+        Some(RegionRedirectSuggestion {
+            variant_id: variant.id.clone(),
+            hreflang: variant.hreflang(),
+            url: variant.url_for(path),
+            reason: reason.to_string(),
+        })
+    }
+
+    /// Validate the mapping itself (codes, duplicates, dangling references)
+    pub fn validate(&self) -> Vec<HreflangIssue> {
+        let mut issues = Vec::new();
+        let mut seen: HashMap<String, &str> = HashMap::new();
+
+        for variant in self.variants.values() {
+            let code = variant.hreflang();
+            if !is_valid_hreflang(&code) {
+                issues.push(HreflangIssue::new(
+                    HreflangIssueKind::InvalidCode,
+                    &variant.id,
+                    format!("'{}' is not a valid hreflang code", code),
+                ));
+            }
+            if let Some(other) = seen.insert(code.to_lowercase(), &variant.id) {
+                issues.push(HreflangIssue::new(
+                    HreflangIssueKind::DuplicateCode,
+                    &variant.id,
+                    format!("'{}' is also used by variant '{}'", code, other),
+                ));
+            }
+        }
+
+        let mut countries: Vec<_> = self.countries.iter().collect();
+        countries.sort();
+        for (country, variant_id) in countries {
+            if !self.variants.contains_key(variant_id) {
+                issues.push(HreflangIssue::new(
+                    HreflangIssueKind::UnknownVariant,
+                    variant_id,
+                    format!("Country '{}' maps to an unknown variant", country),
+                ));
+            }
+        }
+
+        if let Some(default) = &self.x_default {
+            if !self.variants.contains_key(default) {
+                issues.push(HreflangIssue::new(
+                    HreflangIssueKind::UnknownVariant,
+                    default,
+                    "x-default references an unknown variant".to_string(),
+                ));
+            }
+        }
+
+        issues
+    }
+}
+
+/// Check a hreflang value: `x-default`, or a two- or three-letter language
+/// optionally followed by a script (`zh-Hant`) and a country or UN M.49
+/// area code (`en-GB`, `es-419`)
+pub fn is_valid_hreflang(code: &str) -> bool {
+    if code == "x-default" {
+        return true;
+    }
+    let alpha =
+        |part: &str, len: usize| part.len() == len && part.chars().all(|c| c.is_ascii_alphabetic());
+    let mut parts = code.split('-').peekable();
+    let language = parts.next().unwrap_or_default();
+    if !alpha(language, 2) && !alpha(language, 3) {
+        return false;
+    }
+    if parts.peek().is_some_and(|part| alpha(part, 4)) {
+        parts.next();
+    }
+    let region_ok = match parts.next() {
+        None => true,
+        Some(region) => {
+            alpha(region, 2) || (region.len() == 3 && region.chars().all(|c| c.is_ascii_digit()))
+        }
+    };
+    region_ok && parts.next().is_none()
+}
+
+/// Validate hreflang annotations across a set of crawled pages.
+///
+/// `pages` maps each page URL to the hreflang links found on it. Every
+/// alternate that is itself in the crawl must link back to the page;
+/// alternates outside the crawl are not checked for reciprocity.
+pub fn validate_hreflang(pages: &HashMap<String, Vec<HreflangLink>>) -> Vec<HreflangIssue> {
+    let mut issues = Vec::new();
+    let mut urls: Vec<&String> = pages.keys().collect();
+    urls.sort();
+
+    for url in urls {
+        let links = &pages[url];
+        let mut codes: HashMap<String, &str> = HashMap::new();
+        let mut x_defaults = 0;
+
+        for link in links {
+            if !is_valid_hreflang(&link.hreflang) {
+                issues.push(HreflangIssue::new(
+                    HreflangIssueKind::InvalidCode,
+                    url,
+                    format!("'{}' is not a valid hreflang code", link.hreflang),
+                ));
+            }
+            if link.hreflang == "x-default" {
+                x_defaults += 1;
+                continue;
+            }
+            if let Some(other) = codes.insert(link.hreflang.to_lowercase(), &link.href) {
+                if other != link.href {
+                    issues.push(HreflangIssue::new(
+                        HreflangIssueKind::DuplicateCode,
+                        url,
+                        format!(
+                            "'{}' points to both {} and {}",
+                            link.hreflang, other, link.href
+                        ),
+                    ));
+                }
+            }
+        }
+
+        if x_defaults > 1 {
+            issues.push(HreflangIssue::new(
+                HreflangIssueKind::MultipleXDefault,
+                url,
+                format!("{} x-default links", x_defaults),
+            ));
+        }
+
+        if !links.is_empty() && !links.iter().any(|l| &l.href == url) {
+            issues.push(HreflangIssue::new(
+                HreflangIssueKind::MissingSelfReference,
+                url,
+                "Page does not reference itself".to_string(),
+            ));
+        }
+
+        let mut checked = HashSet::new();
+        for link in links {
+            if &link.href == url || !checked.insert(&link.href) {
+                continue;
+            }
+            if let Some(target_links) = pages.get(&link.href) {
+                if !target_links.iter().any(|l| &l.href == url) {
+                    issues.push(HreflangIssue::new(
+                        HreflangIssueKind::MissingReturnLink,
+                        url,
+                        format!("{} does not link back", link.href),
+                    ));
+                }
+            }
+        }
+    }
+
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mapping() -> RegionMapping {
+        RegionMapping::new()
+            .add_variant(RegionVariant::new("us", "en", "https://example.com").for_country("us"))
+            .add_variant(RegionVariant::new("uk", "en", "https://example.co.uk").for_country("gb"))
+            .add_variant(RegionVariant::new("fr", "fr", "https://example.fr/"))
+            .map_country("US", "us")
+            .map_country("GB", "uk")
+            .map_country("FR", "fr")
+            .map_country("BE", "fr")
+            .with_x_default("us")
+    }
+
+    fn alternates() -> HashMap<String, String> {
+        HashMap::from([
+            ("us".to_string(), "/pricing".to_string()),
+            ("uk".to_string(), "/pricing".to_string()),
+            ("fr".to_string(), "/tarifs".to_string()),
+        ])
+    }
+
+    #[test]
+    fn test_hreflang_generation() {
+        let links = mapping().hreflang_links(&alternates());
+        assert_eq!(links.len(), 4);
+        assert!(links.contains(&HreflangLink {
+            hreflang: "en-GB".into(),
+            href: "https://example.co.uk/pricing".into(),
+        }));
+        assert!(links.contains(&HreflangLink {
+            hreflang: "fr".into(),
+            href: "https://example.fr/tarifs".into(),
+        }));
+        assert_eq!(links.last().unwrap().hreflang, "x-default");
+        assert!(mapping().validate().is_empty());
+    }
+
+    #[test]
+    fn test_redirect_suggestions() {
+        let mapping = mapping();
+        let alternates = alternates();
+
+        let suggestion = mapping
+            .suggest_redirect("us", Some("be"), None, &alternates)
+            .unwrap();
+        assert_eq!(suggestion.variant_id, "fr");
+        assert_eq!(suggestion.reason, "country");
+
+        let suggestion = mapping
+            .suggest_redirect("us", None, Some("en-GB,en;q=0.8"), &alternates)
+            .unwrap();
+        assert_eq!(suggestion.url, "https://example.co.uk/pricing");
+
+        assert!(mapping
+            .suggest_redirect("uk", Some("GB"), None, &alternates)
+            .is_none());
+    }
+
+    #[test]
+    fn test_config_validation() {
+        let mapping = mapping()
+            .add_variant(RegionVariant::new("ca", "en", "https://example.ca").for_country("GB"))
+            .map_country("DE", "de");
+        let kinds: Vec<_> = mapping.validate().into_iter().map(|i| i.kind).collect();
+        assert!(kinds.contains(&HreflangIssueKind::DuplicateCode));
+        assert!(kinds.contains(&HreflangIssueKind::UnknownVariant));
+        assert!(is_valid_hreflang("zh-Hant"));
+        assert!(is_valid_hreflang("zh-Hant-TW"));
+        assert!(is_valid_hreflang("es-419"));
+        assert!(is_valid_hreflang("fil-PH"));
+        assert!(!is_valid_hreflang("english"));
+        assert!(!is_valid_hreflang("en-GB-US"));
+        assert!(!is_valid_hreflang("es-41"));
+    }
+
+    #[test]
+    fn test_tag_escapes_attributes() {
+        let link = HreflangLink {
+            hreflang: "en".into(),
+            href: r#"https://example.com/?a=1&b="><script>"#.into(),
+        };
+        assert_eq!(
+            link.to_tag(),
+            r#"<link rel="alternate" hreflang="en" href="https://example.com/?a=1&amp;b=&quot;&gt;&lt;script&gt;">"#
+        );
+    }
+
+    #[test]
+    fn test_reciprocity_validation() {
+        let link = |code: &str, href: &str| HreflangLink {
+            hreflang: code.into(),
+            href: href.into(),
+        };
+        let pages = HashMap::from([
+            (
+                "https://example.com/a".to_string(),
+                vec![
+                    link("en", "https://example.com/a"),
+                    link("fr", "https://example.fr/a"),
+                ],
+            ),
+            (
+                "https://example.fr/a".to_string(),
+                vec![link("fr", "https://example.fr/a")],
+            ),
+        ]);
+
+        let issues = validate_hreflang(&pages);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].kind, HreflangIssueKind::MissingReturnLink);
+        assert_eq!(issues[0].subject, "https://example.com/a");
+    }
+}
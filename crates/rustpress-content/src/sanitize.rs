@@ -8,12 +8,13 @@
 //! - URL scheme validation
 //! - Custom attribute sanitizers
 //! - KSES-style capability-based filtering
+//! - Reports of the tags and attributes a pass stripped
 
 use ammonia::{Builder, Url, UrlRelative};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use thiserror::Error;
 
 /// Sanitization errors
@@ -66,6 +67,9 @@ pub struct SanitizeConfig {
     /// Additional allowed tags
     pub additional_tags: HashSet<String>,
 
+    /// Tags removed even when the level or `additional_tags` allow them
+    pub blocked_tags: HashSet<String>,
+
     /// Additional allowed attributes (tag -> attributes)
     pub additional_attributes: HashMap<String, HashSet<String>>,
 
@@ -136,6 +140,7 @@ impl Default for SanitizeConfig {
         Self {
             level: SanitizationLevel::Standard,
             additional_tags: HashSet::new(),
+            blocked_tags: HashSet::new(),
             additional_attributes: HashMap::new(),
             url_schemes: default_url_schemes(),
             strip_comments: true,
@@ -533,6 +538,13 @@ impl Sanitizer {
         }
     }
 
+    /// Sanitize HTML content and report what was stripped
+    pub fn sanitize_with_report(&self, html: &str) -> (String, SanitizeReport) {
+        let clean = self.sanitize(html);
+        let report = SanitizeReport::compare(html, &clean);
+        (clean, report)
+    }
+
     /// Strip all HTML tags
     fn strip_all_html(&self, html: &str) -> String {
         let re = Regex::new(r"<[^>]+>").unwrap();
@@ -560,14 +572,23 @@ impl Sanitizer {
             _ => HashSet::new(),
         };
 
-        // Add additional custom tags
+        // Add additional custom tags, then drop blocked ones
         let all_tags: HashSet<&str> = tags
             .into_iter()
             .chain(self.config.additional_tags.iter().map(|s| s.as_str()))
+            .filter(|tag| !self.config.blocked_tags.contains(*tag))
             .collect();
 
         builder.tags(all_tags.clone());
 
+        // Elements removed with their content; ammonia rejects a tag that is
+        // both allowed and cleaned, so allowed ones (`style` when relaxed) stay
+        let clean_content: HashSet<&str> = ["script", "style"]
+            .into_iter()
+            .filter(|tag| !all_tags.contains(tag))
+            .collect();
+        builder.clean_content_tags(clean_content);
+
         // Get attributes for tags
         let mut attrs = standard_attributes();
 
@@ -831,6 +852,103 @@ impl Sanitizer {
     }
 }
 
+/// What a sanitization pass stripped from its input
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SanitizeReport {
+    /// Removed elements by tag name
+    pub removed_tags: BTreeMap<String, usize>,
+
+    /// Removed attributes, keyed `tag[attribute]`
+    pub removed_attributes: BTreeMap<String, usize>,
+
+    /// Removed HTML comments
+    pub removed_comments: usize,
+}
+
+impl SanitizeReport {
+    /// Compare the markup before and after sanitization. Elements and
+    /// attributes the sanitizer added (such as `rel` on links) are ignored.
+    pub fn compare(before: &str, after: &str) -> Self {
+        let (tags_before, attributes_before, comments_before) = markup_inventory(before);
+        let (tags_after, attributes_after, comments_after) = markup_inventory(after);
+
+        let removed = |before: BTreeMap<String, usize>, after: &BTreeMap<String, usize>| {
+            before
+                .into_iter()
+                .filter_map(|(key, count)| {
+                    let kept = after.get(&key).copied().unwrap_or(0);
+                    (count > kept).then(|| (key, count - kept))
+                })
+                .collect()
+        };
+
+        Self {
+            removed_tags: removed(tags_before, &tags_after),
+            removed_attributes: removed(attributes_before, &attributes_after),
+            removed_comments: comments_before.saturating_sub(comments_after),
+        }
+    }
+
+    /// Whether nothing was stripped
+    pub fn is_empty(&self) -> bool {
+        self.removed_tags.is_empty()
+            && self.removed_attributes.is_empty()
+            && self.removed_comments == 0
+    }
+
+    /// Add the findings of another pass
+    pub fn merge(&mut self, other: SanitizeReport) {
+        for (tag, count) in other.removed_tags {
+            *self.removed_tags.entry(tag).or_insert(0) += count;
+        }
+        for (attribute, count) in other.removed_attributes {
+            *self.removed_attributes.entry(attribute).or_insert(0) += count;
+        }
+        self.removed_comments += other.removed_comments;
+    }
+}
+
+/// Count opening tags, their attributes and comments in markup
+fn markup_inventory(html: &str) -> (BTreeMap<String, usize>, BTreeMap<String, usize>, usize) {
+    let comment_re = Regex::new(r"(?s)<!--.*?-->").unwrap();
+    let tag_re = Regex::new(r#"<([a-zA-Z][a-zA-Z0-9:-]*)((?:[^>"']|"[^"]*"|'[^']*')*)>"#).unwrap();
+    let attr_re = Regex::new(r#"([^\s="'<>/]+)(?:\s*=\s*(?:"[^"]*"|'[^']*'|[^\s>]+))?"#).unwrap();
+
+    let comments = comment_re.find_iter(html).count();
+    let html = comment_re.replace_all(html, "");
+
+    let mut tags = BTreeMap::new();
+    let mut attributes = BTreeMap::new();
+    for caps in tag_re.captures_iter(&html) {
+        let tag = caps[1].to_lowercase();
+        for attr in attr_re.captures_iter(&caps[2]) {
+            let key = format!("{}[{}]", tag, attr[1].to_lowercase());
+            *attributes.entry(key).or_insert(0) += 1;
+        }
+        *tags.entry(tag).or_insert(0) += 1;
+    }
+    (tags, attributes, comments)
+}
+
+/// Sanitize the raw HTML of legacy content: classic (freeform) content
+/// without block markup is sanitized whole, block content only inside
+/// `<!-- wp:html -->` blocks.
+pub fn sanitize_legacy_html(content: &str, sanitizer: &Sanitizer) -> (String, SanitizeReport) {
+    if !content.contains("<!-- wp:") {
+        return sanitizer.sanitize_with_report(content);
+    }
+
+    let block_re =
+        Regex::new(r"(?s)(<!-- wp:html(?:\s+\{.*?\})?\s*-->)(.*?)(<!-- /wp:html -->)").unwrap();
+    let mut report = SanitizeReport::default();
+    let result = block_re.replace_all(content, |caps: &regex::Captures| {
+        let (clean, block_report) = sanitizer.sanitize_with_report(&caps[2]);
+        report.merge(block_report);
+        format!("{}\n{}\n{}", &caps[1], clean, &caps[3])
+    });
+    (result.into_owned(), report)
+}
+
 /// Content context for sanitization
 #[derive(Debug, Clone, Copy)]
 pub enum ContentContext {
@@ -1035,6 +1153,47 @@ mod tests {
         assert_eq!(escaped, "&lt;script&gt;alert(&#39;xss&#39;)&lt;/script&gt;");
     }
 
+    #[test]
+    fn test_blocked_tags_and_report() {
+        let html = r#"<p onclick="steal()">Hi<!-- note --></p><iframe src="https://example.com/embed"></iframe><script>x()</script>"#;
+
+        let relaxed = Sanitizer::with_level(SanitizationLevel::Relaxed);
+        let (clean, report) = relaxed.sanitize_with_report(html);
+        assert!(clean.contains("<iframe"));
+        assert_eq!(report.removed_tags.get("script"), Some(&1));
+        assert_eq!(report.removed_attributes.get("p[onclick]"), Some(&1));
+        assert_eq!(report.removed_comments, 1);
+        assert!(!report.removed_tags.contains_key("iframe"));
+
+        let no_iframes = Sanitizer::new(SanitizeConfig {
+            level: SanitizationLevel::Relaxed,
+            blocked_tags: HashSet::from(["iframe".to_string()]),
+            ..Default::default()
+        });
+        let (clean, report) = no_iframes.sanitize_with_report(html);
+        assert!(!clean.contains("<iframe"));
+        assert_eq!(report.removed_tags.get("iframe"), Some(&1));
+
+        let (_, report) = relaxed.sanitize_with_report("<p>Fine</p>");
+        assert!(report.is_empty());
+    }
+
+    #[test]
+    fn test_sanitize_legacy_html() {
+        let sanitizer = Sanitizer::default();
+        let blocks = "<!-- wp:paragraph --><p>Keep <em>this</em></p><!-- /wp:paragraph -->\n\
+                      <!-- wp:html --><div>Raw<script>x()</script></div><!-- /wp:html -->";
+        let (clean, report) = sanitize_legacy_html(blocks, &sanitizer);
+        assert!(clean.starts_with("<!-- wp:paragraph --><p>Keep <em>this</em></p>"));
+        assert!(clean.contains("<!-- wp:html -->\n<div>Raw</div>\n<!-- /wp:html -->"));
+        assert_eq!(report.removed_tags.get("script"), Some(&1));
+
+        let (clean, report) =
+            sanitize_legacy_html("<p>Classic<script>x()</script></p>", &sanitizer);
+        assert_eq!(clean, "<p>Classic</p>");
+        assert_eq!(report.removed_tags.get("script"), Some(&1));
+    }
+
     #[test]
     fn test_kses_roles() {
         let kses = KsesSanitizer::new();
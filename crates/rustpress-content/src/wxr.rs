@@ -196,7 +196,7 @@ impl WxrParser {
 
         let mut doc = WxrDocument::default();
         let mut buf = Vec::new();
-        let mut current_element = String::new();
+        let mut text = String::new();
         let mut in_channel = false;
         let mut in_item = false;
         let mut current_item = WxrItem::default();
@@ -215,7 +215,7 @@ impl WxrParser {
             match reader.read_event_into(&mut buf) {
                 Ok(Event::Start(e)) => {
                     let name = String::from_utf8_lossy(e.name().as_ref()).to_string();
-                    current_element = name.clone();
+                    text.clear();
 
                     match name.as_str() {
                         "channel" => in_channel = true,
@@ -265,52 +265,16 @@ impl WxrParser {
                 }
                 Ok(Event::End(e)) => {
                     let name = String::from_utf8_lossy(e.name().as_ref()).to_string();
-
-                    match name.as_str() {
-                        "channel" => in_channel = false,
-                        "item" => {
-                            in_item = false;
-                            doc.items.push(current_item.clone());
-                        }
-                        "wp:author" => {
-                            in_author = false;
-                            doc.authors.push(current_author.clone());
-                        }
-                        "wp:category" => {
-                            in_category = false;
-                            doc.categories.push(current_category.clone());
-                        }
-                        "wp:tag" => {
-                            in_tag = false;
-                            doc.tags.push(current_tag.clone());
-                        }
-                        "wp:comment" => {
-                            in_comment = false;
-                            current_item.comments.push(current_comment.clone());
-                        }
-                        "wp:postmeta" => {
-                            in_postmeta = false;
-                            current_item.meta.push(current_meta.clone());
-                        }
-                        "wp:commentmeta" => {
-                            in_postmeta = false;
-                            current_comment.meta.push(current_meta.clone());
-                        }
-                        _ => {}
-                    }
-                    current_element.clear();
-                }
-                Ok(Event::Text(e)) => {
-                    let text = e.unescape().unwrap_or_default().to_string();
+                    let text = std::mem::take(&mut text);
 
                     if in_postmeta {
-                        match current_element.as_str() {
+                        match name.as_str() {
                             "wp:meta_key" => current_meta.key = text,
                             "wp:meta_value" => current_meta.value = text,
                             _ => {}
                         }
                     } else if in_comment {
-                        match current_element.as_str() {
+                        match name.as_str() {
                             "wp:comment_id" => current_comment.id = text.parse().unwrap_or(0),
                             "wp:comment_author" => current_comment.author = text,
                             "wp:comment_author_email" => current_comment.author_email = text,
@@ -330,7 +294,7 @@ impl WxrParser {
                             _ => {}
                         }
                     } else if in_author {
-                        match current_element.as_str() {
+                        match name.as_str() {
                             "wp:author_id" => current_author.id = text.parse().unwrap_or(0),
                             "wp:author_login" => current_author.login = text,
                             "wp:author_email" => current_author.email = text,
@@ -340,7 +304,7 @@ impl WxrParser {
                             _ => {}
                         }
                     } else if in_category {
-                        match current_element.as_str() {
+                        match name.as_str() {
                             "wp:term_id" => current_category.term_id = text.parse().unwrap_or(0),
                             "wp:category_nicename" => current_category.nicename = text,
                             "wp:category_parent" => current_category.parent = text,
@@ -349,7 +313,7 @@ impl WxrParser {
                             _ => {}
                         }
                     } else if in_tag {
-                        match current_element.as_str() {
+                        match name.as_str() {
                             "wp:term_id" => current_tag.term_id = text.parse().unwrap_or(0),
                             "wp:tag_slug" => current_tag.slug = text,
                             "wp:tag_name" => current_tag.name = text,
@@ -357,7 +321,7 @@ impl WxrParser {
                             _ => {}
                         }
                     } else if in_item {
-                        match current_element.as_str() {
+                        match name.as_str() {
                             "title" => current_item.title = text,
                             "link" => current_item.link = text,
                             "pubDate" => current_item.pubdate = parse_rfc2822_date(&text),
@@ -385,10 +349,15 @@ impl WxrParser {
                             "wp:post_password" => current_item.post_password = text,
                             "wp:is_sticky" => current_item.is_sticky = text == "1",
                             "wp:attachment_url" => current_item.attachment_url = Some(text),
+                            "category" => {
+                                if let Some(cat) = current_item.categories.last_mut() {
+                                    cat.name = text;
+                                }
+                            }
                             _ => {}
                         }
                     } else if in_channel {
-                        match current_element.as_str() {
+                        match name.as_str() {
                             "title" => doc.site.title = text,
                             "link" => doc.site.link = text,
                             "description" => doc.site.description = text,
@@ -400,23 +369,48 @@ impl WxrParser {
                             _ => {}
                         }
                     }
-                }
-                Ok(Event::CData(e)) => {
-                    let text = String::from_utf8_lossy(&e).to_string();
 
-                    if in_item {
-                        match current_element.as_str() {
-                            "content:encoded" => current_item.content = text,
-                            "excerpt:encoded" => current_item.excerpt = text,
-                            "category" => {
-                                if let Some(cat) = current_item.categories.last_mut() {
-                                    cat.name = text;
-                                }
-                            }
-                            _ => {}
+                    match name.as_str() {
+                        "channel" => in_channel = false,
+                        "item" => {
+                            in_item = false;
+                            doc.items.push(current_item.clone());
                         }
+                        "wp:author" => {
+                            in_author = false;
+                            doc.authors.push(current_author.clone());
+                        }
+                        "wp:category" => {
+                            in_category = false;
+                            doc.categories.push(current_category.clone());
+                        }
+                        "wp:tag" => {
+                            in_tag = false;
+                            doc.tags.push(current_tag.clone());
+                        }
+                        "wp:comment" => {
+                            in_comment = false;
+                            current_item.comments.push(current_comment.clone());
+                        }
+                        "wp:postmeta" => {
+                            in_postmeta = false;
+                            current_item.meta.push(current_meta.clone());
+                        }
+                        "wp:commentmeta" => {
+                            in_postmeta = false;
+                            current_comment.meta.push(current_meta.clone());
+                        }
+                        _ => {}
                     }
                 }
+                Ok(Event::Text(e)) => {
+                    text.push_str(&e.unescape().unwrap_or_default());
+                }
+                // Most fields are wrapped in CDATA, which may also be split
+                // into several sections
+                Ok(Event::CData(e)) => {
+                    text.push_str(&String::from_utf8_lossy(&e));
+                }
                 Ok(Event::Eof) => break,
                 Err(e) => return Err(WxrError::XmlError(e)),
                 _ => {}
@@ -793,6 +787,59 @@ mod tests {
         assert!(doc.categories.is_empty());
     }
 
+    #[test]
+    fn test_parse_cdata_fields() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<rss version="2.0" xmlns:content="http://purl.org/rss/1.0/modules/content/" xmlns:wp="http://wordpress.org/export/1.2/" xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:excerpt="http://wordpress.org/export/1.2/excerpt/">
+<channel>
+    <title>Old Blog</title>
+    <wp:wxr_version>1.2</wp:wxr_version>
+    <wp:base_site_url>https://old.example.com</wp:base_site_url>
+    <wp:author><wp:author_id>2</wp:author_id><wp:author_login><![CDATA[ada]]></wp:author_login><wp:author_email><![CDATA[ada@example.com]]></wp:author_email><wp:author_display_name><![CDATA[Ada L]]></wp:author_display_name></wp:author>
+    <wp:category><wp:term_id>3</wp:term_id><wp:category_nicename><![CDATA[news]]></wp:category_nicename><wp:category_parent><![CDATA[]]></wp:category_parent><wp:cat_name><![CDATA[News &amp; Notes]]></wp:cat_name></wp:category>
+    <item>
+        <title>Hello &amp; welcome</title>
+        <link>https://old.example.com/2024/01/15/hello/</link>
+        <dc:creator><![CDATA[ada]]></dc:creator>
+        <content:encoded><![CDATA[<p>Uses ]]]]><![CDATA[> inside</p>]]></content:encoded>
+        <wp:post_id>10</wp:post_id>
+        <wp:post_date><![CDATA[2024-01-15 12:30:00]]></wp:post_date>
+        <wp:post_name><![CDATA[hello]]></wp:post_name>
+        <wp:status><![CDATA[publish]]></wp:status>
+        <wp:post_type><![CDATA[post]]></wp:post_type>
+        <category domain="category" nicename="news"><![CDATA[News & Notes]]></category>
+        <category domain="post_tag" nicename="rust">Rust</category>
+        <wp:postmeta><wp:meta_key><![CDATA[_thumbnail_id]]></wp:meta_key><wp:meta_value><![CDATA[11]]></wp:meta_value></wp:postmeta>
+        <wp:comment><wp:comment_id>5</wp:comment_id><wp:comment_author><![CDATA[Bob]]></wp:comment_author><wp:comment_content><![CDATA[Nice]]></wp:comment_content><wp:comment_approved><![CDATA[1]]></wp:comment_approved><wp:comment_parent>0</wp:comment_parent></wp:comment>
+    </item>
+</channel>
+</rss>"#;
+        let doc = WxrParser::parse(xml).unwrap();
+        assert_eq!(doc.site.title, "Old Blog");
+        assert_eq!(doc.site.base_site_url, "https://old.example.com");
+        assert_eq!(doc.authors[0].login, "ada");
+        assert_eq!(doc.authors[0].display_name, "Ada L");
+        assert_eq!(doc.categories[0].nicename, "news");
+        assert_eq!(doc.categories[0].name, "News &amp; Notes");
+
+        let item = &doc.items[0];
+        assert_eq!(item.title, "Hello & welcome");
+        assert_eq!(item.creator, "ada");
+        assert_eq!(item.content, "<p>Uses ]]> inside</p>");
+        assert_eq!(item.post_name, "hello");
+        assert_eq!(item.status, "publish");
+        assert_eq!(item.post_type, "post");
+        assert!(item.post_date.is_some());
+        assert_eq!(item.categories.len(), 2);
+        assert_eq!(item.categories[0].name, "News & Notes");
+        assert_eq!(item.categories[1].domain, "post_tag");
+        assert_eq!(item.categories[1].name, "Rust");
+        assert_eq!(item.meta[0].key, "_thumbnail_id");
+        assert_eq!(item.meta[0].value, "11");
+        assert_eq!(item.comments[0].author, "Bob");
+        assert_eq!(item.comments[0].approved, "1");
+    }
+
     #[test]
     fn test_export_empty_document() {
         let mut doc = WxrDocument::default();
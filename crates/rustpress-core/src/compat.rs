@@ -0,0 +1,415 @@
+//! Compatibility checks for themes and plugins.
+//!
+//! Before an extension is activated its [`CompatProfile`] is checked against
+//! the [`Platform`] it runs on and the profiles of the extensions already
+//! active: the RustPress version it needs, platform features such as
+//! `pgvector` or `redis`, routes and hooks it claims, and extensions it
+//! declares conflicts with. Errors block activation; warnings are reported
+//! but don't.
+
+use crate::error::{Error, Result};
+use crate::tenant::ExtensionKind;
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// Well-known platform features extensions can require
+pub mod features {
+    /// The pgvector PostgreSQL extension
+    pub const PGVECTOR: &str = "pgvector";
+    /// The pg_trgm PostgreSQL extension
+    pub const PG_TRGM: &str = "pg_trgm";
+    /// A Redis server for caching and queues
+    pub const REDIS: &str = "redis";
+    /// Object storage such as S3
+    pub const OBJECT_STORAGE: &str = "object_storage";
+}
+
+/// What the running installation provides
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Platform {
+    /// RustPress version
+    pub version: Version,
+    /// Available features, see [`features`]
+    pub features: BTreeSet<String>,
+}
+
+impl Platform {
+    /// This build of RustPress, without optional features
+    pub fn current() -> Self {
+        Self {
+            version: Version::parse(crate::VERSION).unwrap_or_else(|_| Version::new(0, 0, 0)),
+            features: BTreeSet::new(),
+        }
+    }
+
+    pub fn with_feature(mut self, feature: impl Into<String>) -> Self {
+        self.features.insert(feature.into());
+        self
+    }
+
+    pub fn has_feature(&self, feature: &str) -> bool {
+        self.features.contains(feature)
+    }
+}
+
+impl Default for Platform {
+    fn default() -> Self {
+        Self::current()
+    }
+}
+
+/// A hook callback an extension registers
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HookClaim {
+    pub hook: String,
+    pub priority: i32,
+}
+
+/// A route an extension serves
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RouteClaim {
+    pub method: String,
+    pub path: String,
+}
+
+impl RouteClaim {
+    pub fn new(method: impl Into<String>, path: impl Into<String>) -> Self {
+        Self {
+            method: method.into().to_uppercase(),
+            path: path.into(),
+        }
+    }
+
+    /// Whether both routes would match the same requests. Path parameters
+    /// (`:id`, `{id}`) match any segment
+    pub fn overlaps(&self, other: &RouteClaim) -> bool {
+        let segments = |path: &str| -> Vec<String> {
+            path.trim_matches('/')
+                .split('/')
+                .map(|s| {
+                    if s.starts_with(':') || (s.starts_with('{') && s.ends_with('}')) {
+                        "*".to_string()
+                    } else {
+                        s.to_string()
+                    }
+                })
+                .collect()
+        };
+        let (a, b) = (segments(&self.path), segments(&other.path));
+
+        self.method == other.method
+            && a.len() == b.len()
+            && a.iter()
+                .zip(&b)
+                .all(|(x, y)| x == y || x == "*" || y == "*")
+    }
+}
+
+/// What an extension requires and claims, checked before activation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompatProfile {
+    pub kind: ExtensionKind,
+    pub id: String,
+    pub version: String,
+    /// Minimum RustPress version
+    #[serde(default)]
+    pub min_rustpress_version: Option<Version>,
+    /// Platform features the extension needs
+    #[serde(default)]
+    pub required_features: Vec<String>,
+    #[serde(default)]
+    pub hooks: Vec<HookClaim>,
+    #[serde(default)]
+    pub routes: Vec<RouteClaim>,
+    /// Extensions that can't be active alongside this one
+    #[serde(default)]
+    pub conflicts: Vec<String>,
+}
+
+impl CompatProfile {
+    pub fn new(kind: ExtensionKind, id: impl Into<String>, version: impl Into<String>) -> Self {
+        Self {
+            kind,
+            id: id.into(),
+            version: version.into(),
+            min_rustpress_version: None,
+            required_features: Vec::new(),
+            hooks: Vec::new(),
+            routes: Vec::new(),
+            conflicts: Vec::new(),
+        }
+    }
+
+    pub fn min_rustpress_version(mut self, version: Version) -> Self {
+        self.min_rustpress_version = Some(version);
+        self
+    }
+
+    pub fn requires_feature(mut self, feature: impl Into<String>) -> Self {
+        self.required_features.push(feature.into());
+        self
+    }
+
+    pub fn hook(mut self, hook: impl Into<String>, priority: i32) -> Self {
+        self.hooks.push(HookClaim {
+            hook: hook.into(),
+            priority,
+        });
+        self
+    }
+
+    pub fn route(mut self, method: impl Into<String>, path: impl Into<String>) -> Self {
+        self.routes.push(RouteClaim::new(method, path));
+        self
+    }
+
+    pub fn conflicts_with(mut self, id: impl Into<String>) -> Self {
+        self.conflicts.push(id.into());
+        self
+    }
+}
+
+/// How serious a compatibility issue is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Blocks activation
+    Error,
+    /// Reported only
+    Warning,
+}
+
+/// What a compatibility issue is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    RustpressVersion,
+    MissingFeature,
+    RouteConflict,
+    HookConflict,
+    DeclaredConflict,
+}
+
+/// A single compatibility problem
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompatIssue {
+    pub severity: Severity,
+    pub kind: IssueKind,
+    pub message: String,
+    /// Extension the issue involves, for conflicts
+    #[serde(default)]
+    pub other: Option<String>,
+}
+
+/// Result of checking one extension
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompatReport {
+    pub kind: ExtensionKind,
+    pub id: String,
+    pub version: String,
+    pub issues: Vec<CompatIssue>,
+}
+
+impl CompatReport {
+    /// Whether nothing blocks activation
+    pub fn is_compatible(&self) -> bool {
+        self.errors().next().is_none()
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = &CompatIssue> {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity == Severity::Error)
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &CompatIssue> {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity == Severity::Warning)
+    }
+
+    /// An [`Error::Incompatible`] listing the blocking issues
+    pub fn to_error(&self) -> Error {
+        Error::Incompatible {
+            kind: self.kind.to_string(),
+            extension_id: self.id.clone(),
+            issues: self.errors().map(|issue| issue.message.clone()).collect(),
+        }
+    }
+
+    /// `Ok` when compatible, otherwise [`CompatReport::to_error`]
+    pub fn into_result(self) -> Result<()> {
+        if self.is_compatible() {
+            return Ok(());
+        }
+        Err(self.to_error())
+    }
+}
+
+/// Check an extension against the platform and the active extensions.
+/// `active` may contain the extension itself, which is skipped
+pub fn check(
+    platform: &Platform,
+    profile: &CompatProfile,
+    active: &[CompatProfile],
+) -> CompatReport {
+    let mut issues = Vec::new();
+
+    if let Some(min) = &profile.min_rustpress_version {
+        if &platform.version < min {
+            issues.push(CompatIssue {
+                severity: Severity::Error,
+                kind: IssueKind::RustpressVersion,
+                message: format!(
+                    "Requires RustPress {} or later, this is {}",
+                    min, platform.version
+                ),
+                other: None,
+            });
+        }
+    }
+
+    for feature in &profile.required_features {
+        if !platform.has_feature(feature) {
+            issues.push(CompatIssue {
+                severity: Severity::Error,
+                kind: IssueKind::MissingFeature,
+                message: format!("Requires {}, which is not available", feature),
+                other: None,
+            });
+        }
+    }
+
+    let others = active
+        .iter()
+        .filter(|other| !(other.kind == profile.kind && other.id == profile.id));
+    for other in others {
+        if profile.conflicts.contains(&other.id) || other.conflicts.contains(&profile.id) {
+            issues.push(CompatIssue {
+                severity: Severity::Error,
+                kind: IssueKind::DeclaredConflict,
+                message: format!("Conflicts with {} '{}'", other.kind, other.id),
+                other: Some(other.id.clone()),
+            });
+        }
+
+        for route in &profile.routes {
+            if let Some(taken) = other.routes.iter().find(|r| r.overlaps(route)) {
+                issues.push(CompatIssue {
+                    severity: Severity::Error,
+                    kind: IssueKind::RouteConflict,
+                    message: format!(
+                        "Route {} {} overlaps {} {} of {} '{}'",
+                        route.method, route.path, taken.method, taken.path, other.kind, other.id
+                    ),
+                    other: Some(other.id.clone()),
+                });
+            }
+        }
+
+        for hook in &profile.hooks {
+            if other.hooks.contains(hook) {
+                issues.push(CompatIssue {
+                    severity: Severity::Warning,
+                    kind: IssueKind::HookConflict,
+                    message: format!(
+                        "Hook '{}' at priority {} is also used by {} '{}'; their order depends on load order",
+                        hook.hook, hook.priority, other.kind, other.id
+                    ),
+                    other: Some(other.id.clone()),
+                });
+            }
+        }
+    }
+
+    CompatReport {
+        kind: profile.kind,
+        id: profile.id.clone(),
+        version: profile.version.clone(),
+        issues,
+    }
+}
+
+/// Check every installed extension against the platform and the active
+/// ones
+pub fn audit(
+    platform: &Platform,
+    installed: &[CompatProfile],
+    active: &[CompatProfile],
+) -> Vec<CompatReport> {
+    installed
+        .iter()
+        .map(|profile| check(platform, profile, active))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plugin(id: &str) -> CompatProfile {
+        CompatProfile::new(ExtensionKind::Plugin, id, "1.0.0")
+    }
+
+    #[test]
+    fn test_platform_requirements() {
+        let platform = Platform {
+            version: Version::new(1, 2, 0),
+            features: BTreeSet::new(),
+        }
+        .with_feature(features::REDIS);
+
+        let profile = plugin("search")
+            .min_rustpress_version(Version::new(2, 0, 0))
+            .requires_feature(features::REDIS)
+            .requires_feature(features::PGVECTOR);
+        let report = check(&platform, &profile, &[]);
+
+        let kinds: Vec<IssueKind> = report.errors().map(|i| i.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![IssueKind::RustpressVersion, IssueKind::MissingFeature]
+        );
+        assert!(report.issues[1].message.contains("pgvector"));
+
+        let err = report.into_result().unwrap_err();
+        assert_eq!(err.error_code(), "INCOMPATIBLE");
+        assert!(err.to_string().contains("Requires RustPress 2.0.0"));
+    }
+
+    #[test]
+    fn test_conflicts_with_active_extensions() {
+        let platform = Platform::current();
+        let active = vec![
+            plugin("shop")
+                .route("GET", "/products/:id")
+                .hook("the_content", 10),
+            plugin("legacy-seo"),
+        ];
+
+        let profile = plugin("catalog")
+            .route("get", "/products/{slug}")
+            .route("POST", "/products/:id")
+            .hook("the_content", 10)
+            .hook("the_title", 10)
+            .conflicts_with("legacy-seo");
+        let report = check(&platform, &profile, &active);
+
+        let issues: Vec<(Severity, IssueKind)> =
+            report.issues.iter().map(|i| (i.severity, i.kind)).collect();
+        assert_eq!(
+            issues,
+            vec![
+                (Severity::Error, IssueKind::RouteConflict),
+                (Severity::Warning, IssueKind::HookConflict),
+                (Severity::Error, IssueKind::DeclaredConflict),
+            ]
+        );
+        assert!(!report.is_compatible());
+
+        // Re-checking an active extension ignores itself
+        let report = check(&platform, &active[0], &active);
+        assert!(report.issues.is_empty());
+    }
+}
@@ -2,7 +2,10 @@
 //!
 //! Supports TOML, YAML, and environment variable configuration.
 
+use crate::http::HttpConfig;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::IpAddr;
 use std::path::PathBuf;
 use std::time::Duration;
 
@@ -31,6 +34,15 @@ pub struct AppConfig {
     pub jobs: JobConfig,
     /// API configuration
     pub api: ApiConfig,
+    /// Outbound HTTP configuration
+    #[serde(default)]
+    pub http: HttpConfig,
+    /// Middleware stack per route group
+    #[serde(default)]
+    pub middleware: MiddlewareConfig,
+    /// Full-text search backend
+    #[serde(default)]
+    pub search: SearchConfig,
 }
 
 impl Default for AppConfig {
@@ -47,10 +59,19 @@ impl Default for AppConfig {
             multitenancy: MultitenancyConfig::default(),
             jobs: JobConfig::default(),
             api: ApiConfig::default(),
+            http: HttpConfig::default(),
+            middleware: MiddlewareConfig::default(),
+            search: SearchConfig::default(),
         }
     }
 }
 
+/// Current deployment environment (`RUSTPRESS_ENV`, defaults to production
+/// so an unset variable never blocks a live site from search engines)
+pub fn current_environment() -> String {
+    std::env::var("RUSTPRESS_ENV").unwrap_or_else(|_| "production".to_string())
+}
+
 /// Server configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServerConfig {
@@ -72,6 +93,9 @@ pub struct ServerConfig {
     pub tls_key_path: Option<PathBuf>,
     /// Graceful shutdown timeout in seconds
     pub shutdown_timeout_secs: u64,
+    /// Reverse proxies allowed to report the client address in X-Forwarded-For
+    #[serde(default)]
+    pub trusted_proxies: Vec<IpAddr>,
 }
 
 impl Default for ServerConfig {
@@ -86,6 +110,7 @@ impl Default for ServerConfig {
             tls_cert_path: None,
             tls_key_path: None,
             shutdown_timeout_secs: 30,
+            trusted_proxies: Vec::new(),
         }
     }
 }
@@ -290,6 +315,42 @@ impl Default for StorageConfig {
     }
 }
 
+/// Full-text search configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchConfig {
+    /// Search backend type
+    pub backend: SearchBackend,
+    /// Meilisearch or Elasticsearch base URL
+    pub url: Option<String>,
+    /// Meilisearch key or Elasticsearch API key
+    pub api_key: Option<String>,
+    /// Index name on Meilisearch or Elasticsearch
+    pub index: String,
+    /// PostgreSQL text search configuration, e.g. `english` or `simple`
+    pub language: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchBackend {
+    Postgres,
+    Meilisearch,
+    Elasticsearch,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self {
+            backend: SearchBackend::Postgres,
+            url: None,
+            api_key: None,
+            index: "rustpress".to_string(),
+            language: "english".to_string(),
+        }
+    }
+}
+
 /// Logging configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LoggingConfig {
@@ -352,6 +413,44 @@ impl Default for MetricsConfig {
     }
 }
 
+/// Middleware stack configuration
+///
+/// Middleware is named in snake case (`rate_limit`, `page_cache`, ...).
+/// Requests use the stack of the group with the longest matching path
+/// prefix, or the default stack.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MiddlewareConfig {
+    /// Stack for requests no group matches
+    #[serde(default)]
+    pub default: MiddlewareStackConfig,
+    /// Stacks for route groups
+    #[serde(default)]
+    pub groups: Vec<MiddlewareGroupConfig>,
+}
+
+/// Order and selection of middleware in a stack
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MiddlewareStackConfig {
+    /// Every enabled middleware, outermost first; empty keeps the built-in
+    /// order
+    #[serde(default)]
+    pub order: Vec<String>,
+    /// Middleware left out of the stack
+    #[serde(default)]
+    pub disable: Vec<String>,
+}
+
+/// Middleware stack for the routes under a path prefix
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MiddlewareGroupConfig {
+    pub name: String,
+    /// Path prefix, matched on segment boundaries (`/api` matches
+    /// `/api/v1` but not `/apis`)
+    pub path_prefix: String,
+    #[serde(flatten)]
+    pub stack: MiddlewareStackConfig,
+}
+
 /// Rate limiting configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RateLimitConfig {
@@ -359,6 +458,16 @@ pub struct RateLimitConfig {
     pub enabled: bool,
     /// Requests per window
     pub requests_per_window: u32,
+    /// Requests per window for self-identified or verified crawlers
+    #[serde(default = "default_known_bot_requests")]
+    pub known_bot_requests_per_window: u32,
+    /// Requests per window for suspected bots
+    #[serde(default = "default_suspected_bot_requests")]
+    pub suspected_bot_requests_per_window: u32,
+    /// Requests per window for human traffic from specific countries
+    /// (ISO 3166-1 alpha-2 code to limit); needs the GeoIP database
+    #[serde(default)]
+    pub country_requests_per_window: HashMap<String, u32>,
     /// Window size in seconds
     pub window_secs: u64,
     /// Rate limit by IP
@@ -376,6 +485,9 @@ impl Default for RateLimitConfig {
         Self {
             enabled: true,
             requests_per_window: 100,
+            known_bot_requests_per_window: default_known_bot_requests(),
+            suspected_bot_requests_per_window: default_suspected_bot_requests(),
+            country_requests_per_window: HashMap::new(),
             window_secs: 60,
             by_ip: true,
             by_user: true,
@@ -390,6 +502,14 @@ impl Default for RateLimitConfig {
     }
 }
 
+fn default_known_bot_requests() -> u32 {
+    300
+}
+
+fn default_suspected_bot_requests() -> u32 {
+    20
+}
+
 /// Multi-tenancy configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MultitenancyConfig {
@@ -403,6 +523,11 @@ pub struct MultitenancyConfig {
     pub default_tenant: Option<String>,
     /// Maximum tenants allowed (None = unlimited)
     pub max_tenants: Option<u32>,
+    /// Domain the network is served from; sites get subdomains of it.
+    /// When unset, the first label of any host with three or more labels
+    /// names the site
+    #[serde(default)]
+    pub network_domain: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -437,6 +562,7 @@ impl Default for MultitenancyConfig {
             identification: TenantIdentification::Subdomain,
             default_tenant: Some("default".to_string()),
             max_tenants: None,
+            network_domain: None,
         }
     }
 }
@@ -0,0 +1,788 @@
+//! Encrypted configuration bundles.
+//!
+//! A bundle is a TOML configuration file in which every value is
+//! encrypted, in the style of sops: keys stay readable so a bundle can be
+//! reviewed and diffed, values read `ENC[chacha20poly1305,data:...,type:...]`.
+//! Each value is bound to its dotted path, so values can't be moved
+//! around, and a MAC over all of them catches values being removed or
+//! added.
+//!
+//! Values are encrypted with a random data key, which is stored wrapped
+//! for every recipient in the `[rustpress_bundle]` table, as with age.
+//! Recipients are [`KeyProvider`]s: a key in an environment variable or a
+//! key held by a KMS (HashiCorp Vault's transit engine). Managed hosting
+//! providers ship a bundle per customer and hand the server only the key,
+//! so no secret sits in a plain file.
+
+use crate::error::{Error, Result};
+use crate::http::{HttpClient, HttpConfig};
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Nonce};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use toml::{Table, Value};
+
+/// Environment variable naming the bundle the server loads at startup
+pub const BUNDLE_PATH_ENV: &str = "RUSTPRESS_CONFIG_BUNDLE";
+
+/// Environment variable holding a base64-encoded bundle key
+pub const BUNDLE_KEY_ENV: &str = "RUSTPRESS_BUNDLE_KEY";
+
+/// Environment variable naming the Vault transit key bundles are wrapped
+/// with; `VAULT_ADDR` and `VAULT_TOKEN` locate and authenticate to Vault
+pub const BUNDLE_KMS_KEY_ENV: &str = "RUSTPRESS_BUNDLE_KMS_KEY";
+
+/// Table holding the bundle's metadata
+pub const METADATA_TABLE: &str = "rustpress_bundle";
+
+const FORMAT_VERSION: i64 = 1;
+const KEY_SIZE: usize = 32;
+const NONCE_SIZE: usize = 12;
+const CIPHER: &str = "chacha20poly1305";
+
+/// Key the values of a bundle are encrypted with
+pub struct DataKey([u8; KEY_SIZE]);
+
+impl DataKey {
+    /// A new random key
+    pub fn generate() -> Self {
+        let mut key = [0u8; KEY_SIZE];
+        rand::thread_rng().fill_bytes(&mut key);
+        Self(key)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let key: [u8; KEY_SIZE] = bytes
+            .try_into()
+            .map_err(|_| config_error(format!("Bundle keys must be {} bytes", KEY_SIZE)))?;
+        Ok(Self(key))
+    }
+
+    pub fn as_bytes(&self) -> &[u8; KEY_SIZE] {
+        &self.0
+    }
+
+    fn cipher(&self) -> ChaCha20Poly1305 {
+        ChaCha20Poly1305::new(self.0.as_slice().into())
+    }
+
+    fn seal(&self, plaintext: &[u8], aad: &str) -> Result<Vec<u8>> {
+        let mut nonce = [0u8; NONCE_SIZE];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let ciphertext = self
+            .cipher()
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: plaintext,
+                    aad: aad.as_bytes(),
+                },
+            )
+            .map_err(|_| config_error("Failed to encrypt bundle value"))?;
+
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&ciphertext);
+        Ok(sealed)
+    }
+
+    fn open(&self, sealed: &[u8], aad: &str) -> Result<Vec<u8>> {
+        if sealed.len() < NONCE_SIZE {
+            return Err(config_error(format!(
+                "Malformed encrypted value at {}",
+                aad
+            )));
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_SIZE);
+        self.cipher()
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: aad.as_bytes(),
+                },
+            )
+            .map_err(|_| config_error(format!("Failed to decrypt bundle value at {}", aad)))
+    }
+}
+
+impl Drop for DataKey {
+    fn drop(&mut self) {
+        self.0.fill(0);
+    }
+}
+
+/// Holder of a key bundles are wrapped for
+#[async_trait]
+pub trait KeyProvider: Send + Sync {
+    /// How the provider is recorded in bundles, e.g.
+    /// `env:RUSTPRESS_BUNDLE_KEY`
+    fn recipient(&self) -> String;
+
+    /// Encrypt a bundle's data key for this recipient
+    async fn wrap(&self, key: &DataKey) -> Result<String>;
+
+    /// Recover a data key wrapped by [`wrap`](Self::wrap)
+    async fn unwrap(&self, wrapped: &str) -> Result<DataKey>;
+}
+
+/// A key kept in an environment variable, base64-encoded
+pub struct EnvKey {
+    var: String,
+    key: DataKey,
+}
+
+impl EnvKey {
+    /// Read the key from `var`
+    pub fn from_env(var: &str) -> Result<Self> {
+        let encoded =
+            std::env::var(var).map_err(|_| config_error(format!("{} is not set", var)))?;
+        Self::new(var, &encoded)
+    }
+
+    /// A key as it would be read from `var`
+    pub fn new(var: &str, encoded: &str) -> Result<Self> {
+        let bytes = BASE64
+            .decode(encoded.trim())
+            .map_err(|_| config_error(format!("{} is not valid base64", var)))?;
+        Ok(Self {
+            var: var.to_string(),
+            key: DataKey::from_bytes(&bytes)?,
+        })
+    }
+
+    /// A new random key, base64-encoded for an environment variable
+    pub fn generate() -> String {
+        BASE64.encode(DataKey::generate().as_bytes())
+    }
+}
+
+#[async_trait]
+impl KeyProvider for EnvKey {
+    fn recipient(&self) -> String {
+        format!("env:{}", self.var)
+    }
+
+    async fn wrap(&self, key: &DataKey) -> Result<String> {
+        Ok(BASE64.encode(self.key.seal(key.as_bytes(), &self.recipient())?))
+    }
+
+    async fn unwrap(&self, wrapped: &str) -> Result<DataKey> {
+        let sealed = BASE64
+            .decode(wrapped)
+            .map_err(|_| config_error("Wrapped bundle key is not valid base64"))?;
+        DataKey::from_bytes(&self.key.open(&sealed, &self.recipient())?)
+    }
+}
+
+/// A key held by HashiCorp Vault's transit secrets engine, which wraps
+/// and unwraps data keys without ever handing out the key itself
+pub struct VaultTransitKey {
+    http: HttpClient,
+    address: String,
+    token: String,
+    key_name: String,
+}
+
+impl VaultTransitKey {
+    pub fn new(
+        address: impl Into<String>,
+        token: impl Into<String>,
+        key_name: impl Into<String>,
+    ) -> Result<Self> {
+        Ok(Self {
+            http: HttpClient::new(HttpConfig::default())?,
+            address: address.into().trim_end_matches('/').to_string(),
+            token: token.into(),
+            key_name: key_name.into(),
+        })
+    }
+
+    /// Configure from `VAULT_ADDR`, `VAULT_TOKEN` and the key named by
+    /// [`BUNDLE_KMS_KEY_ENV`]; `None` unless all three are set
+    pub fn from_env() -> Result<Option<Self>> {
+        match (
+            std::env::var("VAULT_ADDR"),
+            std::env::var("VAULT_TOKEN"),
+            std::env::var(BUNDLE_KMS_KEY_ENV),
+        ) {
+            (Ok(address), Ok(token), Ok(key_name)) => Self::new(address, token, key_name).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    async fn transit(&self, operation: &str, body: serde_json::Value) -> Result<serde_json::Value> {
+        let url = format!(
+            "{}/v1/transit/{}/{}",
+            self.address, operation, self.key_name
+        );
+        let response = self
+            .http
+            .send(
+                self.http
+                    .post(&url)
+                    .header("X-Vault-Token", &self.token)
+                    .json(&body),
+            )
+            .await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(config_error(format!(
+                "Vault refused to {} with key '{}' ({})",
+                operation, self.key_name, status
+            )));
+        }
+        let body: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| config_error(format!("Invalid response from Vault: {}", e)))?;
+        Ok(body["data"].clone())
+    }
+}
+
+#[async_trait]
+impl KeyProvider for VaultTransitKey {
+    fn recipient(&self) -> String {
+        format!("vault-transit:{}", self.key_name)
+    }
+
+    async fn wrap(&self, key: &DataKey) -> Result<String> {
+        let data = self
+            .transit(
+                "encrypt",
+                serde_json::json!({ "plaintext": BASE64.encode(key.as_bytes()) }),
+            )
+            .await?;
+        data["ciphertext"]
+            .as_str()
+            .map(String::from)
+            .ok_or_else(|| config_error("Vault returned no ciphertext"))
+    }
+
+    async fn unwrap(&self, wrapped: &str) -> Result<DataKey> {
+        let data = self
+            .transit("decrypt", serde_json::json!({ "ciphertext": wrapped }))
+            .await?;
+        let plaintext = data["plaintext"]
+            .as_str()
+            .ok_or_else(|| config_error("Vault returned no plaintext"))?;
+        let bytes = BASE64
+            .decode(plaintext)
+            .map_err(|_| config_error("Vault returned an invalid key"))?;
+        DataKey::from_bytes(&bytes)
+    }
+}
+
+/// Key providers configured through the environment: [`EnvKey`] from
+/// [`BUNDLE_KEY_ENV`] and [`VaultTransitKey`]
+pub fn env_providers() -> Result<Vec<Box<dyn KeyProvider>>> {
+    let mut providers: Vec<Box<dyn KeyProvider>> = Vec::new();
+    if std::env::var_os(BUNDLE_KEY_ENV).is_some() {
+        providers.push(Box::new(EnvKey::from_env(BUNDLE_KEY_ENV)?));
+    }
+    if let Some(vault) = VaultTransitKey::from_env()? {
+        providers.push(Box::new(vault));
+    }
+    Ok(providers)
+}
+
+/// A data key wrapped for one recipient
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WrappedKey {
+    pub recipient: String,
+    pub key: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Metadata {
+    version: i64,
+    cipher: String,
+    mac: String,
+    updated_at: String,
+    recipients: Vec<WrappedKey>,
+}
+
+/// An encrypted configuration bundle
+#[derive(Debug, Clone)]
+pub struct ConfigBundle {
+    metadata: Metadata,
+    values: Table,
+}
+
+impl ConfigBundle {
+    /// Encrypt `config` for every provider
+    pub async fn seal(config: &Table, providers: &[&dyn KeyProvider]) -> Result<Self> {
+        if providers.is_empty() {
+            return Err(config_error("A bundle needs at least one recipient"));
+        }
+
+        let key = DataKey::generate();
+        let mut recipients = Vec::with_capacity(providers.len());
+        for provider in providers {
+            recipients.push(WrappedKey {
+                recipient: provider.recipient(),
+                key: provider.wrap(&key).await?,
+            });
+        }
+
+        let mut bundle = Self {
+            metadata: Metadata {
+                version: FORMAT_VERSION,
+                cipher: CIPHER.to_string(),
+                mac: String::new(),
+                updated_at: String::new(),
+                recipients,
+            },
+            values: Table::new(),
+        };
+        bundle.update(&key, config)?;
+        Ok(bundle)
+    }
+
+    /// Parse a bundle file's contents
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut values: Table = text
+            .parse()
+            .map_err(|e| config_error(format!("Invalid bundle: {}", e)))?;
+        let metadata: Metadata = values
+            .remove(METADATA_TABLE)
+            .ok_or_else(|| config_error(format!("Not a bundle: no [{}] table", METADATA_TABLE)))?
+            .try_into()
+            .map_err(|e| config_error(format!("Invalid [{}] table: {}", METADATA_TABLE, e)))?;
+
+        if metadata.version != FORMAT_VERSION || metadata.cipher != CIPHER {
+            return Err(config_error(format!(
+                "Unsupported bundle format {} ({})",
+                metadata.version, metadata.cipher
+            )));
+        }
+        Ok(Self { metadata, values })
+    }
+
+    /// Read and parse a bundle file
+    pub fn read(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path).map_err(|e| {
+            config_error(format!("Failed to read bundle {}: {}", path.display(), e))
+        })?;
+        Self::parse(&text)
+    }
+
+    /// The bundle as written to a file
+    pub fn to_toml_string(&self) -> Result<String> {
+        let mut document = self.values.clone();
+        document.insert(
+            METADATA_TABLE.to_string(),
+            Value::try_from(&self.metadata)
+                .map_err(|e| config_error(format!("Failed to encode bundle: {}", e)))?,
+        );
+        toml::to_string_pretty(&document)
+            .map_err(|e| config_error(format!("Failed to encode bundle: {}", e)))
+    }
+
+    /// Recipients the data key is wrapped for
+    pub fn recipients(&self) -> Vec<&str> {
+        self.metadata
+            .recipients
+            .iter()
+            .map(|r| r.recipient.as_str())
+            .collect()
+    }
+
+    /// When the values were last encrypted (RFC 3339)
+    pub fn updated_at(&self) -> &str {
+        &self.metadata.updated_at
+    }
+
+    /// Dotted paths of the encrypted settings; readable without a key
+    pub fn keys(&self) -> Vec<String> {
+        fn collect(table: &Table, prefix: &str, out: &mut Vec<String>) {
+            for (name, value) in table {
+                let path = child_path(prefix, name);
+                match value {
+                    Value::Table(inner) => collect(inner, &path, out),
+                    _ => out.push(path),
+                }
+            }
+        }
+
+        let mut keys = Vec::new();
+        collect(&self.values, "", &mut keys);
+        keys
+    }
+
+    /// Recover the data key through the first provider the bundle is
+    /// wrapped for
+    pub async fn unlock(&self, providers: &[&dyn KeyProvider]) -> Result<DataKey> {
+        for provider in providers {
+            let recipient = provider.recipient();
+            if let Some(wrapped) = self
+                .metadata
+                .recipients
+                .iter()
+                .find(|r| r.recipient == recipient)
+            {
+                return provider.unwrap(&wrapped.key).await;
+            }
+        }
+        Err(config_error(format!(
+            "No key available for this bundle; it is encrypted for: {}",
+            self.recipients().join(", ")
+        )))
+    }
+
+    /// Decrypt the configuration, checking nothing was altered
+    pub fn decrypt(&self, key: &DataKey) -> Result<Table> {
+        let mut digest = Sha256::new();
+        let config = decrypt_table(key, &self.values, "", &mut digest)?;
+
+        let mac = decrypt_scalar(key, &self.metadata.mac, METADATA_TABLE)?;
+        if mac.as_str() != Some(hex(&digest.finalize()).as_str()) {
+            return Err(config_error(
+                "Bundle MAC mismatch: values were added or removed",
+            ));
+        }
+        Ok(config)
+    }
+
+    /// Unlock and decrypt in one go
+    pub async fn open(&self, providers: &[&dyn KeyProvider]) -> Result<Table> {
+        let key = self.unlock(providers).await?;
+        self.decrypt(&key)
+    }
+
+    /// Replace the configuration, keeping the data key and recipients
+    pub fn update(&mut self, key: &DataKey, config: &Table) -> Result<()> {
+        if config.contains_key(METADATA_TABLE) {
+            return Err(config_error(format!(
+                "[{}] is reserved for bundle metadata",
+                METADATA_TABLE
+            )));
+        }
+        let mut digest = Sha256::new();
+        self.values = encrypt_table(key, config, "", &mut digest)?;
+        self.metadata.mac =
+            encrypt_scalar(key, &Value::String(hex(&digest.finalize())), METADATA_TABLE)?;
+        self.metadata.updated_at = chrono::Utc::now().to_rfc3339();
+        Ok(())
+    }
+
+    /// Wrap the data key for another recipient
+    pub async fn add_recipient(&mut self, key: &DataKey, provider: &dyn KeyProvider) -> Result<()> {
+        let recipient = provider.recipient();
+        let wrapped = provider.wrap(key).await?;
+        self.metadata
+            .recipients
+            .retain(|r| r.recipient != recipient);
+        self.metadata.recipients.push(WrappedKey {
+            recipient,
+            key: wrapped,
+        });
+        Ok(())
+    }
+
+    /// Stop wrapping the data key for a recipient. Returns whether it was
+    /// one; the last recipient can't be removed.
+    ///
+    /// The removed recipient may still hold the data key, so follow up
+    /// with [`rotate`](Self::rotate).
+    pub fn remove_recipient(&mut self, recipient: &str) -> Result<bool> {
+        let before = self.metadata.recipients.len();
+        if before == 1 && self.metadata.recipients[0].recipient == recipient {
+            return Err(config_error("A bundle needs at least one recipient"));
+        }
+        self.metadata
+            .recipients
+            .retain(|r| r.recipient != recipient);
+        Ok(self.metadata.recipients.len() != before)
+    }
+
+    /// Re-encrypt the values under a new data key, wrapped for every
+    /// recipient.
+    ///
+    /// The current key is unlocked through `providers`, which must also
+    /// hold a provider for each recipient to wrap the new key for.
+    pub async fn rotate(&mut self, providers: &[&dyn KeyProvider]) -> Result<()> {
+        let config = self.open(providers).await?;
+
+        let key = DataKey::generate();
+        let mut recipients = Vec::with_capacity(self.metadata.recipients.len());
+        let mut missing = Vec::new();
+        for current in &self.metadata.recipients {
+            match providers
+                .iter()
+                .find(|p| p.recipient() == current.recipient)
+            {
+                Some(provider) => recipients.push(WrappedKey {
+                    recipient: current.recipient.clone(),
+                    key: provider.wrap(&key).await?,
+                }),
+                None => missing.push(current.recipient.as_str()),
+            }
+        }
+        if !missing.is_empty() {
+            return Err(config_error(format!(
+                "No key available to re-encrypt the bundle for: {}",
+                missing.join(", ")
+            )));
+        }
+
+        self.metadata.recipients = recipients;
+        self.update(&key, &config)
+    }
+}
+
+/// Load the bundle named by [`BUNDLE_PATH_ENV`] with the keys configured
+/// in the environment. `None` if no bundle is configured
+pub async fn load_from_env() -> Result<Option<Table>> {
+    let Some(path) = std::env::var_os(BUNDLE_PATH_ENV) else {
+        return Ok(None);
+    };
+    let bundle = ConfigBundle::read(Path::new(&path))?;
+
+    let providers = env_providers()?;
+    if providers.is_empty() {
+        return Err(config_error(format!(
+            "{} is set but no bundle key is: set {} or VAULT_ADDR, VAULT_TOKEN and {}",
+            BUNDLE_PATH_ENV, BUNDLE_KEY_ENV, BUNDLE_KMS_KEY_ENV
+        )));
+    }
+    let providers: Vec<&dyn KeyProvider> = providers.iter().map(|p| p.as_ref()).collect();
+    bundle.open(&providers).await.map(Some)
+}
+
+fn child_path(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+fn encrypt_table(key: &DataKey, table: &Table, path: &str, digest: &mut Sha256) -> Result<Table> {
+    table
+        .iter()
+        .map(|(name, value)| {
+            let path = child_path(path, name);
+            Ok((name.clone(), encrypt_value(key, value, &path, digest)?))
+        })
+        .collect()
+}
+
+fn encrypt_value(key: &DataKey, value: &Value, path: &str, digest: &mut Sha256) -> Result<Value> {
+    match value {
+        Value::Table(table) => Ok(Value::Table(encrypt_table(key, table, path, digest)?)),
+        Value::Array(items) => items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| encrypt_value(key, item, &format!("{}[{}]", path, i), digest))
+            .collect::<Result<Vec<_>>>()
+            .map(Value::Array),
+        scalar => {
+            digest_scalar(digest, path, scalar);
+            encrypt_scalar(key, scalar, path).map(Value::String)
+        }
+    }
+}
+
+fn decrypt_table(key: &DataKey, table: &Table, path: &str, digest: &mut Sha256) -> Result<Table> {
+    table
+        .iter()
+        .map(|(name, value)| {
+            let path = child_path(path, name);
+            Ok((name.clone(), decrypt_value(key, value, &path, digest)?))
+        })
+        .collect()
+}
+
+fn decrypt_value(key: &DataKey, value: &Value, path: &str, digest: &mut Sha256) -> Result<Value> {
+    match value {
+        Value::Table(table) => Ok(Value::Table(decrypt_table(key, table, path, digest)?)),
+        Value::Array(items) => items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| decrypt_value(key, item, &format!("{}[{}]", path, i), digest))
+            .collect::<Result<Vec<_>>>()
+            .map(Value::Array),
+        Value::String(sealed) => {
+            let scalar = decrypt_scalar(key, sealed, path)?;
+            digest_scalar(digest, path, &scalar);
+            Ok(scalar)
+        }
+        _ => Err(config_error(format!("Unencrypted value at {}", path))),
+    }
+}
+
+/// `ENC[chacha20poly1305,data:<base64 nonce and ciphertext>,type:<type>]`
+fn encrypt_scalar(key: &DataKey, value: &Value, path: &str) -> Result<String> {
+    let (kind, plaintext) = match value {
+        Value::String(s) => ("str", s.clone()),
+        Value::Integer(i) => ("int", i.to_string()),
+        Value::Float(f) => ("float", f.to_string()),
+        Value::Boolean(b) => ("bool", b.to_string()),
+        Value::Datetime(d) => ("datetime", d.to_string()),
+        Value::Array(_) | Value::Table(_) => {
+            return Err(config_error(format!("Not a scalar at {}", path)))
+        }
+    };
+    let sealed = key.seal(plaintext.as_bytes(), path)?;
+    Ok(format!(
+        "ENC[{},data:{},type:{}]",
+        CIPHER,
+        BASE64.encode(sealed),
+        kind
+    ))
+}
+
+fn decrypt_scalar(key: &DataKey, sealed: &str, path: &str) -> Result<Value> {
+    let malformed = || config_error(format!("Malformed encrypted value at {}", path));
+    let inner = sealed
+        .strip_prefix("ENC[")
+        .and_then(|s| s.strip_suffix(']'))
+        .ok_or_else(|| config_error(format!("Unencrypted value at {}", path)))?;
+    let mut parts = inner.split(',');
+    let (Some(CIPHER), Some(data), Some(kind), None) = (
+        parts.next(),
+        parts.next().and_then(|p| p.strip_prefix("data:")),
+        parts.next().and_then(|p| p.strip_prefix("type:")),
+        parts.next(),
+    ) else {
+        return Err(malformed());
+    };
+
+    let sealed = BASE64.decode(data).map_err(|_| malformed())?;
+    let plaintext = String::from_utf8(key.open(&sealed, path)?).map_err(|_| malformed())?;
+    match kind {
+        "str" => Ok(Value::String(plaintext)),
+        "int" => plaintext
+            .parse()
+            .map(Value::Integer)
+            .map_err(|_| malformed()),
+        "float" => plaintext.parse().map(Value::Float).map_err(|_| malformed()),
+        "bool" => plaintext
+            .parse()
+            .map(Value::Boolean)
+            .map_err(|_| malformed()),
+        "datetime" => plaintext
+            .parse()
+            .map(Value::Datetime)
+            .map_err(|_| malformed()),
+        _ => Err(malformed()),
+    }
+}
+
+fn digest_scalar(digest: &mut Sha256, path: &str, value: &Value) {
+    digest.update(path.as_bytes());
+    digest.update([0]);
+    digest.update(value.to_string().as_bytes());
+    digest.update([0]);
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn config_error(message: impl Into<String>) -> Error {
+    Error::Configuration {
+        message: message.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> Table {
+        r#"
+            [database]
+            database_url = "postgres://customer:s3cret@db/customer"
+            max_connections = 20
+
+            [auth]
+            jwt_secret = "not-for-your-eyes"
+            allowed_origins = ["https://example.com", "https://www.example.com"]
+        "#
+        .parse()
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_seal_and_open() {
+        let host = EnvKey::new("HOST_KEY", &EnvKey::generate()).unwrap();
+        let customer = EnvKey::new("CUSTOMER_KEY", &EnvKey::generate()).unwrap();
+
+        let bundle = ConfigBundle::seal(&config(), &[&host, &customer])
+            .await
+            .unwrap();
+        let text = bundle.to_toml_string().unwrap();
+        assert!(!text.contains("s3cret"));
+        assert!(text.contains("database_url = \"ENC[chacha20poly1305,"));
+
+        // Either recipient can open it
+        let parsed = ConfigBundle::parse(&text).unwrap();
+        assert_eq!(parsed.open(&[&customer]).await.unwrap(), config());
+        assert_eq!(parsed.open(&[&host]).await.unwrap(), config());
+
+        let stranger = EnvKey::new("HOST_KEY", &EnvKey::generate()).unwrap();
+        assert!(parsed.open(&[&stranger]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_tampering_is_detected() {
+        let key = EnvKey::new("KEY", &EnvKey::generate()).unwrap();
+        let bundle = ConfigBundle::seal(&config(), &[&key]).await.unwrap();
+        let data_key = bundle.unlock(&[&key]).await.unwrap();
+
+        // Moving a value to another key
+        let mut moved = bundle.clone();
+        let secret = moved.values["auth"]["jwt_secret"].clone();
+        moved.values["database"]
+            .as_table_mut()
+            .unwrap()
+            .insert("database_url".to_string(), secret);
+        assert!(moved.decrypt(&data_key).is_err());
+
+        // Dropping a value
+        let mut dropped = bundle.clone();
+        dropped.values["auth"]
+            .as_table_mut()
+            .unwrap()
+            .remove("allowed_origins");
+        assert!(dropped.decrypt(&data_key).is_err());
+
+        // Editing keeps the recipients
+        let mut edited = bundle.clone();
+        let mut changed = config();
+        changed["database"]
+            .as_table_mut()
+            .unwrap()
+            .insert("max_connections".to_string(), Value::Integer(50));
+        edited.update(&data_key, &changed).unwrap();
+        assert_eq!(edited.open(&[&key]).await.unwrap(), changed);
+        assert!(edited.remove_recipient("env:KEY").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_rotate_after_removing_a_recipient() {
+        let host = EnvKey::new("HOST_KEY", &EnvKey::generate()).unwrap();
+        let customer = EnvKey::new("CUSTOMER_KEY", &EnvKey::generate()).unwrap();
+        let mut bundle = ConfigBundle::seal(&config(), &[&host, &customer])
+            .await
+            .unwrap();
+        let old_key = bundle.unlock(&[&customer]).await.unwrap();
+
+        // Every recipient needs a provider to wrap the new key
+        assert!(bundle.rotate(&[&host]).await.is_err());
+
+        assert!(bundle.remove_recipient("env:CUSTOMER_KEY").unwrap());
+
+        bundle.rotate(&[&host]).await.unwrap();
+        assert_eq!(bundle.recipients(), vec!["env:HOST_KEY"]);
+        assert_eq!(bundle.open(&[&host]).await.unwrap(), config());
+        // The removed recipient's copy of the old key no longer decrypts
+        assert!(bundle.decrypt(&old_key).is_err());
+    }
+}
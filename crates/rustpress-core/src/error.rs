@@ -23,6 +23,13 @@ pub enum Error {
     #[error("Duplicate entity: {entity_type} already exists")]
     Duplicate { entity_type: String, field: String },
 
+    #[error("Version conflict: {entity_type} {id} is at version {current_version}")]
+    VersionConflict {
+        entity_type: String,
+        id: String,
+        current_version: i64,
+    },
+
     // Authentication errors
     #[error("Authentication failed: {message}")]
     Authentication { message: String },
@@ -93,6 +100,23 @@ pub enum Error {
     #[error("Tenant suspended: {tenant_id}")]
     TenantSuspended { tenant_id: String },
 
+    #[error("{kind} '{extension_id}' is not allowed for {scope}")]
+    ExtensionNotAllowed {
+        kind: String,
+        extension_id: String,
+        scope: String,
+    },
+
+    #[error("{message}")]
+    QuotaExceeded { resource: String, message: String },
+
+    #[error("{kind} '{extension_id}' is incompatible: {}", issues.join("; "))]
+    Incompatible {
+        kind: String,
+        extension_id: String,
+        issues: Vec<String>,
+    },
+
     // Hook errors
     #[error("Hook error: {hook_name} - {message}")]
     Hook { hook_name: String, message: String },
@@ -281,6 +305,40 @@ impl Error {
         }
     }
 
+    /// Create a version conflict error for an optimistic locking failure
+    pub fn version_conflict(
+        entity_type: impl Into<String>,
+        id: impl Into<String>,
+        current_version: i64,
+    ) -> Self {
+        Error::VersionConflict {
+            entity_type: entity_type.into(),
+            id: id.into(),
+            current_version,
+        }
+    }
+
+    /// Create an error for a theme or plugin outside a scope's allowlist
+    pub fn extension_not_allowed(
+        kind: impl Into<String>,
+        extension_id: impl Into<String>,
+        scope: impl Into<String>,
+    ) -> Self {
+        Error::ExtensionNotAllowed {
+            kind: kind.into(),
+            extension_id: extension_id.into(),
+            scope: scope.into(),
+        }
+    }
+
+    /// Create an error for usage that would take a site over its quota
+    pub fn quota_exceeded(resource: impl Into<String>, message: impl Into<String>) -> Self {
+        Error::QuotaExceeded {
+            resource: resource.into(),
+            message: message.into(),
+        }
+    }
+
     /// Create an internal error
     pub fn internal(message: impl Into<String>) -> Self {
         Error::Internal {
@@ -408,10 +466,12 @@ impl Error {
             Error::Authentication { .. } | Error::TokenExpired | Error::InvalidToken { .. } => 401,
             Error::Authorization { .. } => 403,
             Error::Validation(_) | Error::InvalidInput { .. } => 400,
-            Error::Duplicate { .. } => 409,
+            Error::Duplicate { .. } | Error::VersionConflict { .. } => 409,
             Error::RateLimited { .. } => 429,
             Error::ServiceUnavailable { .. } | Error::ShutdownInProgress => 503,
             Error::TenantNotFound { .. } | Error::TenantSuspended { .. } => 403,
+            Error::ExtensionNotAllowed { .. } | Error::QuotaExceeded { .. } => 403,
+            Error::Incompatible { .. } => 409,
             _ => 500,
         }
     }
@@ -422,6 +482,7 @@ impl Error {
             Error::Database { .. } => "DATABASE_ERROR",
             Error::NotFound { .. } => "NOT_FOUND",
             Error::Duplicate { .. } => "DUPLICATE",
+            Error::VersionConflict { .. } => "VERSION_CONFLICT",
             Error::Authentication { .. } => "AUTH_FAILED",
             Error::Authorization { .. } => "FORBIDDEN",
             Error::TokenExpired => "TOKEN_EXPIRED",
@@ -440,6 +501,9 @@ impl Error {
             Error::RateLimited { .. } => "RATE_LIMITED",
             Error::TenantNotFound { .. } => "TENANT_NOT_FOUND",
             Error::TenantSuspended { .. } => "TENANT_SUSPENDED",
+            Error::ExtensionNotAllowed { .. } => "EXTENSION_NOT_ALLOWED",
+            Error::QuotaExceeded { .. } => "QUOTA_EXCEEDED",
+            Error::Incompatible { .. } => "INCOMPATIBLE",
             Error::Hook { .. } => "HOOK_ERROR",
             Error::Network { .. } => "NETWORK_ERROR",
             Error::ServiceUnavailable { .. } => "SERVICE_UNAVAILABLE",
@@ -476,6 +540,7 @@ mod tests {
             .status_code(),
             429
         );
+        assert_eq!(Error::version_conflict("Post", "123", 4).status_code(), 409);
     }
 
     #[test]
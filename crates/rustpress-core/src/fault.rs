@@ -0,0 +1,400 @@
+//! Fault injection for resilience testing.
+//!
+//! Injects latency and errors into database, cache, storage and outbound
+//! HTTP calls so retries, timeouts and fallbacks can be exercised in
+//! development and staging. Rules fire for a percentage of calls and can be
+//! narrowed to request routes and, for HTTP, to destination hosts. An
+//! injector built for any other environment never accepts rules.
+
+use crate::error::{Error, Result};
+use dashmap::DashMap;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Environments in which faults may be injected
+pub const FAULT_ENVIRONMENTS: [&str; 3] = ["development", "staging", "test"];
+
+/// Longest latency a single rule may add
+pub const MAX_FAULT_LATENCY_MS: u64 = 60_000;
+
+tokio::task_local! {
+    /// Path of the request the current task is serving
+    static ROUTE: String;
+}
+
+/// Dependency a fault is injected into
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FaultTarget {
+    Database,
+    Cache,
+    Storage,
+    Http,
+}
+
+impl FaultTarget {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Database => "database",
+            Self::Cache => "cache",
+            Self::Storage => "storage",
+            Self::Http => "http",
+        }
+    }
+
+    /// Error a failing call of this dependency would return
+    fn error(&self) -> Error {
+        let message = format!("Injected {} fault", self.as_str());
+        match self {
+            Self::Database => Error::database(message),
+            Self::Cache => Error::Cache { message },
+            Self::Storage => Error::storage(message),
+            Self::Http => Error::Network {
+                message,
+                source: None,
+            },
+        }
+    }
+}
+
+/// Latency and/or error injected into a share of calls
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FaultRule {
+    #[serde(default = "Uuid::new_v4")]
+    pub id: Uuid,
+    pub target: FaultTarget,
+    /// Share of matching calls affected, 0-100
+    pub percentage: f64,
+    /// Delay added before the call
+    #[serde(default)]
+    pub latency_ms: u64,
+    /// Whether the call fails after the delay
+    #[serde(default)]
+    pub error: bool,
+    /// Request path prefixes the rule is limited to. Empty matches every
+    /// call, including those made outside of requests.
+    #[serde(default)]
+    pub routes: Vec<String>,
+    /// Destination hosts an HTTP rule is limited to; empty matches all
+    #[serde(default)]
+    pub hosts: Vec<String>,
+}
+
+impl FaultRule {
+    pub fn validate(&self) -> Result<()> {
+        if !(0.0..=100.0).contains(&self.percentage) {
+            return Err(Error::invalid_input(
+                "percentage",
+                "must be between 0 and 100",
+            ));
+        }
+        if self.latency_ms == 0 && !self.error {
+            return Err(Error::invalid_input(
+                "latency_ms",
+                "a rule must add latency, an error or both",
+            ));
+        }
+        if self.latency_ms > MAX_FAULT_LATENCY_MS {
+            return Err(Error::invalid_input(
+                "latency_ms",
+                format!("must be at most {}", MAX_FAULT_LATENCY_MS),
+            ));
+        }
+        if !self.hosts.is_empty() && self.target != FaultTarget::Http {
+            return Err(Error::invalid_input(
+                "hosts",
+                "only HTTP rules can target hosts",
+            ));
+        }
+        Ok(())
+    }
+
+    fn matches(&self, target: FaultTarget, route: Option<&str>, host: Option<&str>) -> bool {
+        self.target == target
+            && (self.routes.is_empty()
+                || route.is_some_and(|route| {
+                    self.routes
+                        .iter()
+                        .any(|prefix| route.starts_with(prefix.as_str()))
+                }))
+            && (self.hosts.is_empty()
+                || host.is_some_and(|host| self.hosts.iter().any(|h| h.eq_ignore_ascii_case(host))))
+    }
+}
+
+/// How often a rule has fired
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct FaultHits {
+    pub delayed: u64,
+    pub failed: u64,
+}
+
+#[derive(Debug, Default)]
+struct HitCounters {
+    delayed: AtomicU64,
+    failed: AtomicU64,
+}
+
+#[derive(Debug)]
+struct Inner {
+    allowed: bool,
+    rules: RwLock<Vec<FaultRule>>,
+    hits: DashMap<Uuid, HitCounters>,
+}
+
+/// Shared set of fault rules, consulted by the instrumented dependencies
+#[derive(Debug, Clone)]
+pub struct FaultInjector {
+    inner: Arc<Inner>,
+}
+
+impl FaultInjector {
+    /// Injector that accepts rules when `allowed`
+    pub fn new(allowed: bool) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                allowed,
+                rules: RwLock::new(Vec::new()),
+                hits: DashMap::new(),
+            }),
+        }
+    }
+
+    /// Injector that only accepts rules in development and staging
+    pub fn for_environment(environment: &str) -> Self {
+        Self::new(FAULT_ENVIRONMENTS.contains(&environment))
+    }
+
+    /// Injector that never injects anything
+    pub fn disabled() -> Self {
+        Self::new(false)
+    }
+
+    pub fn is_allowed(&self) -> bool {
+        self.inner.allowed
+    }
+
+    pub fn rules(&self) -> Vec<FaultRule> {
+        self.inner.rules.read().clone()
+    }
+
+    pub fn hits(&self) -> HashMap<Uuid, FaultHits> {
+        self.inner
+            .hits
+            .iter()
+            .map(|entry| {
+                let hits = FaultHits {
+                    delayed: entry.delayed.load(Ordering::Relaxed),
+                    failed: entry.failed.load(Ordering::Relaxed),
+                };
+                (*entry.key(), hits)
+            })
+            .collect()
+    }
+
+    /// Add a rule, replacing one with the same id
+    pub fn add_rule(&self, rule: FaultRule) -> Result<FaultRule> {
+        if !self.inner.allowed {
+            return Err(Error::Configuration {
+                message: "Fault injection is only available in development and staging".to_string(),
+            });
+        }
+        rule.validate()?;
+
+        let mut rules = self.inner.rules.write();
+        rules.retain(|r| r.id != rule.id);
+        rules.push(rule.clone());
+        self.inner.hits.insert(rule.id, HitCounters::default());
+        tracing::warn!(
+            rule_id = %rule.id,
+            target = rule.target.as_str(),
+            percentage = rule.percentage,
+            latency_ms = rule.latency_ms,
+            error = rule.error,
+            "Fault injection rule added"
+        );
+
+        Ok(rule)
+    }
+
+    /// Remove a rule; returns whether it existed
+    pub fn remove_rule(&self, id: Uuid) -> bool {
+        let mut rules = self.inner.rules.write();
+        let before = rules.len();
+        rules.retain(|r| r.id != id);
+        self.inner.hits.remove(&id);
+        before != rules.len()
+    }
+
+    /// Remove every rule
+    pub fn clear(&self) {
+        self.inner.rules.write().clear();
+        self.inner.hits.clear();
+    }
+
+    /// Run `future` as part of serving `route`, so route-targeted rules
+    /// apply to the calls it makes
+    pub async fn scope<F: Future>(route: String, future: F) -> F::Output {
+        ROUTE.scope(route, future).await
+    }
+
+    /// Apply the rules matching a call to `target`: sleep for their
+    /// latency, then fail if any of them injects an error
+    pub async fn inject(&self, target: FaultTarget, host: Option<&str>) -> Result<()> {
+        let (latency, fail) = self.roll(target, host);
+        if !latency.is_zero() {
+            tokio::time::sleep(latency).await;
+        }
+        if fail {
+            return Err(target.error());
+        }
+        Ok(())
+    }
+
+    /// Decide the latency and failure for one call
+    fn roll(&self, target: FaultTarget, host: Option<&str>) -> (Duration, bool) {
+        let rules = self.inner.rules.read();
+        if rules.is_empty() {
+            return (Duration::ZERO, false);
+        }
+
+        let route = ROUTE.try_with(|route| route.clone()).ok();
+        let mut latency_ms = 0;
+        let mut fail = false;
+        for rule in rules
+            .iter()
+            .filter(|rule| rule.matches(target, route.as_deref(), host))
+        {
+            if rand::random::<f64>() * 100.0 >= rule.percentage {
+                continue;
+            }
+            if let Some(hits) = self.inner.hits.get(&rule.id) {
+                if rule.latency_ms > 0 {
+                    hits.delayed.fetch_add(1, Ordering::Relaxed);
+                }
+                if rule.error {
+                    hits.failed.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+            latency_ms += rule.latency_ms;
+            fail |= rule.error;
+        }
+
+        (Duration::from_millis(latency_ms), fail)
+    }
+}
+
+impl Default for FaultInjector {
+    fn default() -> Self {
+        Self::disabled()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(target: FaultTarget) -> FaultRule {
+        FaultRule {
+            id: Uuid::new_v4(),
+            target,
+            percentage: 100.0,
+            latency_ms: 0,
+            error: true,
+            routes: Vec::new(),
+            hosts: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_production_refuses_rules() {
+        let faults = FaultInjector::for_environment("production");
+        assert!(!faults.is_allowed());
+        assert!(faults.add_rule(rule(FaultTarget::Cache)).is_err());
+        assert!(FaultInjector::for_environment("staging").is_allowed());
+    }
+
+    #[test]
+    fn test_rule_validation() {
+        let mut invalid = rule(FaultTarget::Database);
+        invalid.percentage = 150.0;
+        assert!(invalid.validate().is_err());
+
+        let mut invalid = rule(FaultTarget::Database);
+        invalid.error = false;
+        assert!(invalid.validate().is_err());
+
+        let mut invalid = rule(FaultTarget::Cache);
+        invalid.hosts = vec!["example.com".to_string()];
+        assert!(invalid.validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_inject_by_target_and_host() {
+        let faults = FaultInjector::new(true);
+        let mut http = rule(FaultTarget::Http);
+        http.hosts = vec!["analyticsdata.googleapis.com".to_string()];
+        let http = faults.add_rule(http).unwrap();
+
+        assert!(faults.inject(FaultTarget::Database, None).await.is_ok());
+        assert!(faults
+            .inject(FaultTarget::Http, Some("example.com"))
+            .await
+            .is_ok());
+        assert!(faults
+            .inject(FaultTarget::Http, Some("analyticsdata.googleapis.com"))
+            .await
+            .is_err());
+        assert_eq!(faults.hits()[&http.id].failed, 1);
+
+        assert!(faults.remove_rule(http.id));
+        assert!(faults
+            .inject(FaultTarget::Http, Some("analyticsdata.googleapis.com"))
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_route_targeting() {
+        let faults = FaultInjector::new(true);
+        let mut cache = rule(FaultTarget::Cache);
+        cache.routes = vec!["/api/v1/posts".to_string()];
+        faults.add_rule(cache).unwrap();
+
+        // Calls outside of a request never match a route-targeted rule
+        assert!(faults.inject(FaultTarget::Cache, None).await.is_ok());
+
+        let posts = FaultInjector::scope(
+            "/api/v1/posts/1".to_string(),
+            faults.inject(FaultTarget::Cache, None),
+        )
+        .await;
+        assert!(posts.is_err());
+
+        let media = FaultInjector::scope(
+            "/api/v1/media".to_string(),
+            faults.inject(FaultTarget::Cache, None),
+        )
+        .await;
+        assert!(media.is_ok());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_latency() {
+        let faults = FaultInjector::new(true);
+        let mut slow = rule(FaultTarget::Storage);
+        slow.error = false;
+        slow.latency_ms = 500;
+        faults.add_rule(slow).unwrap();
+
+        let start = tokio::time::Instant::now();
+        assert!(faults.inject(FaultTarget::Storage, None).await.is_ok());
+        assert!(start.elapsed() >= Duration::from_millis(500));
+    }
+}
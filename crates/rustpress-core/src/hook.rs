@@ -3,11 +3,14 @@
 //! Provides a powerful event-driven architecture for extending functionality.
 
 use async_trait::async_trait;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
+use serde::Serialize;
 use std::any::Any;
 use std::collections::HashMap;
 use std::future::Future;
+use std::marker::PhantomData;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 
 /// Priority levels for hook execution
@@ -28,6 +31,12 @@ impl Default for Priority {
     }
 }
 
+impl From<i32> for Priority {
+    fn from(value: i32) -> Self {
+        Self(value)
+    }
+}
+
 /// Type alias for async action handlers
 pub type ActionHandler = Arc<
     dyn Fn(Arc<dyn Any + Send + Sync>) -> Pin<Box<dyn Future<Output = ()> + Send>> + Send + Sync,
@@ -36,18 +45,75 @@ pub type ActionHandler = Arc<
 /// Type alias for async filter handlers
 pub type FilterHandler<T> = Arc<dyn Fn(T) -> Pin<Box<dyn Future<Output = T> + Send>> + Send + Sync>;
 
+static NEXT_CALLBACK_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Bookkeeping shared by action and filter callbacks
+#[derive(Clone)]
+struct CallbackMeta {
+    id: u64,
+    priority: Priority,
+    plugin_id: Option<String>,
+    /// Present for once-only callbacks; set once the callback has run.
+    /// Shared between clones so a snapshot taken to run the hook consumes
+    /// the registered callback too
+    fired: Option<Arc<AtomicBool>>,
+}
+
+impl CallbackMeta {
+    fn new(priority: Priority, plugin_id: Option<String>, once: bool) -> Self {
+        Self {
+            id: NEXT_CALLBACK_ID.fetch_add(1, Ordering::Relaxed),
+            priority,
+            plugin_id,
+            fired: once.then(|| Arc::new(AtomicBool::new(false))),
+        }
+    }
+
+    /// Whether the callback should run now. Consumes once-only callbacks,
+    /// so concurrent runs of a hook call them at most once
+    fn claim(&self) -> bool {
+        self.fired
+            .as_ref()
+            .is_none_or(|fired| !fired.swap(true, Ordering::SeqCst))
+    }
+
+    /// A once-only callback that has already run
+    fn is_spent(&self) -> bool {
+        self.fired
+            .as_ref()
+            .is_some_and(|fired| fired.load(Ordering::SeqCst))
+    }
+
+    fn info(&self) -> CallbackInfo {
+        CallbackInfo {
+            priority: self.priority.0,
+            plugin_id: self.plugin_id.clone(),
+            once: self.fired.is_some(),
+        }
+    }
+}
+
 /// A registered action callback
 struct ActionCallback {
     handler: ActionHandler,
-    priority: Priority,
-    plugin_id: Option<String>,
+    meta: CallbackMeta,
 }
 
 /// A registered filter callback
 struct FilterCallback<T: Send + 'static> {
     handler: FilterHandler<T>,
-    priority: Priority,
-    plugin_id: Option<String>,
+    meta: CallbackMeta,
+}
+
+/// Insert a callback keeping execution order: higher priority first, and
+/// registration order within a priority
+fn insert_sorted<C>(callbacks: &mut Vec<C>, callback: C, meta: impl Fn(&C) -> &CallbackMeta) {
+    let priority = meta(&callback).priority;
+    let at = callbacks
+        .iter()
+        .position(|cb| meta(cb).priority < priority)
+        .unwrap_or(callbacks.len());
+    callbacks.insert(at, callback);
 }
 
 /// Actions are hooks that perform side effects without modifying data
@@ -64,40 +130,100 @@ impl Action {
         }
     }
 
-    /// Add a callback to this action
-    pub fn add<F, Fut>(&mut self, handler: F, priority: Priority, plugin_id: Option<String>)
+    /// Add a callback to this action, returning its id
+    pub fn add<F, Fut>(
+        &mut self,
+        handler: F,
+        priority: impl Into<Priority>,
+        plugin_id: Option<String>,
+    ) -> u64
+    where
+        F: Fn(Arc<dyn Any + Send + Sync>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.insert(
+            handler,
+            CallbackMeta::new(priority.into(), plugin_id, false),
+        )
+    }
+
+    /// Add a callback that is removed after it first runs
+    pub fn add_once<F, Fut>(
+        &mut self,
+        handler: F,
+        priority: impl Into<Priority>,
+        plugin_id: Option<String>,
+    ) -> u64
+    where
+        F: Fn(Arc<dyn Any + Send + Sync>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.insert(handler, CallbackMeta::new(priority.into(), plugin_id, true))
+    }
+
+    fn insert<F, Fut>(&mut self, handler: F, meta: CallbackMeta) -> u64
     where
         F: Fn(Arc<dyn Any + Send + Sync>) -> Fut + Send + Sync + 'static,
         Fut: Future<Output = ()> + Send + 'static,
     {
         let handler: ActionHandler = Arc::new(move |data| Box::pin(handler(data)));
+        let id = meta.id;
 
-        self.callbacks.push(ActionCallback {
-            handler,
-            priority,
-            plugin_id,
-        });
+        // Higher priority executes first
+        insert_sorted(
+            &mut self.callbacks,
+            ActionCallback { handler, meta },
+            |cb| &cb.meta,
+        );
+        id
+    }
 
-        // Sort by priority (higher priority executes first)
-        self.callbacks.sort_by(|a, b| b.priority.cmp(&a.priority));
+    /// Remove a callback by id. Returns whether it was registered
+    pub fn remove(&mut self, id: u64) -> bool {
+        let before = self.callbacks.len();
+        self.callbacks.retain(|cb| cb.meta.id != id);
+        self.callbacks.len() != before
     }
 
     /// Remove callbacks from a specific plugin
     pub fn remove_plugin(&mut self, plugin_id: &str) {
         self.callbacks
-            .retain(|cb| cb.plugin_id.as_deref() != Some(plugin_id));
+            .retain(|cb| cb.meta.plugin_id.as_deref() != Some(plugin_id));
+    }
+
+    /// Drop once-only callbacks that have run
+    fn prune(&mut self) {
+        self.callbacks.retain(|cb| !cb.meta.is_spent());
+    }
+
+    fn has_once(&self) -> bool {
+        self.callbacks.iter().any(|cb| cb.meta.fired.is_some())
     }
 
     /// Execute all callbacks
     pub async fn execute(&self, data: Arc<dyn Any + Send + Sync>) {
         for callback in &self.callbacks {
-            (callback.handler)(data.clone()).await;
+            if callback.meta.claim() {
+                (callback.handler)(data.clone()).await;
+            }
         }
     }
 
     /// Get the number of registered callbacks
     pub fn callback_count(&self) -> usize {
-        self.callbacks.len()
+        self.callbacks
+            .iter()
+            .filter(|cb| !cb.meta.is_spent())
+            .count()
+    }
+
+    /// Describe the registered callbacks in execution order
+    pub fn callbacks(&self) -> Vec<CallbackInfo> {
+        self.callbacks
+            .iter()
+            .filter(|cb| !cb.meta.is_spent())
+            .map(|cb| cb.meta.info())
+            .collect()
     }
 }
 
@@ -116,40 +242,187 @@ impl<T: Clone + Send + 'static> Filter<T> {
         }
     }
 
-    /// Add a callback to this filter
-    pub fn add<F, Fut>(&mut self, handler: F, priority: Priority, plugin_id: Option<String>)
+    /// Add a callback to this filter, returning its id
+    pub fn add<F, Fut>(
+        &mut self,
+        handler: F,
+        priority: impl Into<Priority>,
+        plugin_id: Option<String>,
+    ) -> u64
     where
         F: Fn(T) -> Fut + Send + Sync + 'static,
         Fut: Future<Output = T> + Send + 'static,
     {
-        let handler: FilterHandler<T> = Arc::new(move |data| Box::pin(handler(data)));
-
-        self.callbacks.push(FilterCallback {
+        self.insert(
             handler,
-            priority,
-            plugin_id,
-        });
+            CallbackMeta::new(priority.into(), plugin_id, false),
+        )
+    }
+
+    /// Add a callback that is removed after it first runs
+    pub fn add_once<F, Fut>(
+        &mut self,
+        handler: F,
+        priority: impl Into<Priority>,
+        plugin_id: Option<String>,
+    ) -> u64
+    where
+        F: Fn(T) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = T> + Send + 'static,
+    {
+        self.insert(handler, CallbackMeta::new(priority.into(), plugin_id, true))
+    }
 
-        self.callbacks.sort_by(|a, b| b.priority.cmp(&a.priority));
+    fn insert<F, Fut>(&mut self, handler: F, meta: CallbackMeta) -> u64
+    where
+        F: Fn(T) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = T> + Send + 'static,
+    {
+        let handler: FilterHandler<T> = Arc::new(move |data| Box::pin(handler(data)));
+        let id = meta.id;
+        insert_sorted(
+            &mut self.callbacks,
+            FilterCallback { handler, meta },
+            |cb| &cb.meta,
+        );
+        id
+    }
+
+    /// Remove a callback by id. Returns whether it was registered
+    pub fn remove(&mut self, id: u64) -> bool {
+        let before = self.callbacks.len();
+        self.callbacks.retain(|cb| cb.meta.id != id);
+        self.callbacks.len() != before
     }
 
     /// Remove callbacks from a specific plugin
     pub fn remove_plugin(&mut self, plugin_id: &str) {
         self.callbacks
-            .retain(|cb| cb.plugin_id.as_deref() != Some(plugin_id));
+            .retain(|cb| cb.meta.plugin_id.as_deref() != Some(plugin_id));
+    }
+
+    /// Drop once-only callbacks that have run
+    fn prune(&mut self) {
+        self.callbacks.retain(|cb| !cb.meta.is_spent());
     }
 
     /// Apply all filters to the data
-    pub async fn apply(&self, mut data: T) -> T {
-        for callback in &self.callbacks {
-            data = (callback.handler)(data).await;
-        }
-        data
+    pub async fn apply(&self, data: T) -> T {
+        run_filters(&self.callbacks, data).await
     }
 
     /// Get the number of registered callbacks
     pub fn callback_count(&self) -> usize {
-        self.callbacks.len()
+        self.callbacks
+            .iter()
+            .filter(|cb| !cb.meta.is_spent())
+            .count()
+    }
+
+    /// Describe the registered callbacks in execution order
+    pub fn callbacks(&self) -> Vec<CallbackInfo> {
+        self.callbacks
+            .iter()
+            .filter(|cb| !cb.meta.is_spent())
+            .map(|cb| cb.meta.info())
+            .collect()
+    }
+}
+
+async fn run_filters<T: Send + 'static>(callbacks: &[FilterCallback<T>], mut data: T) -> T {
+    for callback in callbacks {
+        if callback.meta.claim() {
+            data = (callback.handler)(data).await;
+        }
+    }
+    data
+}
+
+/// Kind of a registered hook
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HookKind {
+    Action,
+    Filter,
+}
+
+/// Introspection record for a single registered callback
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CallbackInfo {
+    /// Numeric priority (higher executes first)
+    pub priority: i32,
+    /// Plugin that registered the callback, if any
+    pub plugin_id: Option<String>,
+    /// Removed after it first runs
+    pub once: bool,
+}
+
+/// Introspection record for a hook and its callbacks
+#[derive(Debug, Clone, Serialize)]
+pub struct HookInfo {
+    pub name: String,
+    pub kind: HookKind,
+    /// Payload type name for filters
+    pub value_type: Option<&'static str>,
+    /// Callbacks in execution order
+    pub callbacks: Vec<CallbackInfo>,
+}
+
+impl HookInfo {
+    /// Distinct plugins with callbacks on this hook
+    pub fn owners(&self) -> Vec<String> {
+        let mut owners: Vec<String> = self
+            .callbacks
+            .iter()
+            .filter_map(|cb| cb.plugin_id.clone())
+            .collect();
+        owners.sort();
+        owners.dedup();
+        owners
+    }
+
+    /// Priorities shared by callbacks from more than one plugin.
+    ///
+    /// Callbacks at the same priority run in registration order, which
+    /// usually depends on plugin load order and is a common source of
+    /// conflicts.
+    pub fn contested_priorities(&self) -> Vec<i32> {
+        let mut by_priority: HashMap<i32, Vec<&str>> = HashMap::new();
+        for cb in &self.callbacks {
+            let owner = cb.plugin_id.as_deref().unwrap_or("");
+            let owners = by_priority.entry(cb.priority).or_default();
+            if !owners.contains(&owner) {
+                owners.push(owner);
+            }
+        }
+        let mut contested: Vec<i32> = by_priority
+            .into_iter()
+            .filter(|(_, owners)| owners.len() > 1)
+            .map(|(priority, _)| priority)
+            .collect();
+        contested.sort_unstable_by(|a, b| b.cmp(a));
+        contested
+    }
+}
+
+/// Identifies a registered callback so it can be removed with
+/// [`HookRegistry::remove`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HookHandle {
+    kind: HookKind,
+    name: String,
+    id: u64,
+}
+
+impl HookHandle {
+    /// Kind of hook the callback is registered on
+    pub fn kind(&self) -> HookKind {
+        self.kind
+    }
+
+    /// Name of the hook the callback is registered on
+    pub fn name(&self) -> &str {
+        &self.name
     }
 }
 
@@ -163,6 +436,71 @@ pub trait Hook: Send + Sync {
     fn name(&self) -> &str;
 }
 
+/// An action name bound to its payload type. Callbacks receive the
+/// payload as `Arc<T>` instead of downcasting `dyn Any` themselves
+pub struct ActionHook<T> {
+    name: &'static str,
+    _payload: PhantomData<fn(T)>,
+}
+
+impl<T> ActionHook<T> {
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            _payload: PhantomData,
+        }
+    }
+}
+
+impl<T> Clone for ActionHook<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for ActionHook<T> {}
+
+#[async_trait]
+impl<T: Send + Sync> Hook for ActionHook<T> {
+    type Data = T;
+
+    fn name(&self) -> &str {
+        self.name
+    }
+}
+
+/// A filter name bound to its payload type
+pub struct FilterHook<T> {
+    name: &'static str,
+    _payload: PhantomData<fn(T) -> T>,
+}
+
+impl<T> FilterHook<T> {
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            _payload: PhantomData,
+        }
+    }
+}
+
+impl<T> Clone for FilterHook<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for FilterHook<T> {}
+
+#[async_trait]
+impl<T: Send + Sync> Hook for FilterHook<T> {
+    type Data = T;
+
+    fn name(&self) -> &str {
+        self.name
+    }
+}
+
 /// Type-erased action storage
 struct ActionStorage {
     actions: HashMap<String, Action>,
@@ -176,11 +514,70 @@ impl ActionStorage {
     }
 }
 
+/// Type-erased view of a filter so the registry can manage filters
+/// without knowing their payload type
+trait ErasedFilter: Send + Sync {
+    fn as_any(&self) -> &dyn Any;
+    fn value_type(&self) -> &'static str;
+    fn callbacks(&self) -> Vec<CallbackInfo>;
+    fn remove(&self, id: u64) -> bool;
+    fn remove_plugin(&self, plugin_id: &str);
+    fn prune(&self);
+}
+
+impl<T: Clone + Send + Sync + 'static> ErasedFilter for RwLock<Filter<T>> {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn value_type(&self) -> &'static str {
+        std::any::type_name::<T>()
+    }
+
+    fn callbacks(&self) -> Vec<CallbackInfo> {
+        self.read().callbacks()
+    }
+
+    fn remove(&self, id: u64) -> bool {
+        self.write().remove(id)
+    }
+
+    fn remove_plugin(&self, plugin_id: &str) {
+        self.write().remove_plugin(plugin_id);
+    }
+
+    fn prune(&self) {
+        self.write().prune();
+    }
+}
+
+/// How often a hook has run, and how many runs are in progress
+#[derive(Debug, Default)]
+struct HookRuns {
+    started: usize,
+    in_progress: usize,
+}
+
+/// Marks a hook run as in progress until dropped
+struct RunGuard<'a> {
+    runs: &'a Mutex<HashMap<(HookKind, String), HookRuns>>,
+    key: (HookKind, String),
+}
+
+impl Drop for RunGuard<'_> {
+    fn drop(&mut self) {
+        if let Some(runs) = self.runs.lock().get_mut(&self.key) {
+            runs.in_progress = runs.in_progress.saturating_sub(1);
+        }
+    }
+}
+
 /// Registry for all hooks in the system
 pub struct HookRegistry {
     actions: RwLock<ActionStorage>,
-    // Filters are stored with type erasure using Any
-    filters: RwLock<HashMap<String, Box<dyn Any + Send + Sync>>>,
+    // Filters are stored with type erasure
+    filters: RwLock<HashMap<String, Box<dyn ErasedFilter>>>,
+    runs: Mutex<HashMap<(HookKind, String), HookRuns>>,
 }
 
 impl HookRegistry {
@@ -188,6 +585,7 @@ impl HookRegistry {
         Self {
             actions: RwLock::new(ActionStorage::new()),
             filters: RwLock::new(HashMap::new()),
+            runs: Mutex::new(HashMap::new()),
         }
     }
 
@@ -195,12 +593,43 @@ impl HookRegistry {
 
     /// Register an action hook
     pub fn add_action<F, Fut>(
+        &self,
+        name: &str,
+        handler: F,
+        priority: impl Into<Priority>,
+        plugin_id: Option<String>,
+    ) -> HookHandle
+    where
+        F: Fn(Arc<dyn Any + Send + Sync>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.insert_action(name, handler, priority.into(), plugin_id, false)
+    }
+
+    /// Register an action callback that is removed after it first runs
+    pub fn add_action_once<F, Fut>(
+        &self,
+        name: &str,
+        handler: F,
+        priority: impl Into<Priority>,
+        plugin_id: Option<String>,
+    ) -> HookHandle
+    where
+        F: Fn(Arc<dyn Any + Send + Sync>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.insert_action(name, handler, priority.into(), plugin_id, true)
+    }
+
+    fn insert_action<F, Fut>(
         &self,
         name: &str,
         handler: F,
         priority: Priority,
         plugin_id: Option<String>,
-    ) where
+        once: bool,
+    ) -> HookHandle
+    where
         F: Fn(Arc<dyn Any + Send + Sync>) -> Fut + Send + Sync + 'static,
         Fut: Future<Output = ()> + Send + 'static,
     {
@@ -209,11 +638,53 @@ impl HookRegistry {
             .actions
             .entry(name.to_string())
             .or_insert_with(|| Action::new(name));
-        action.add(handler, priority, plugin_id);
+        let id = action.insert(handler, CallbackMeta::new(priority, plugin_id, once));
+        HookHandle {
+            kind: HookKind::Action,
+            name: name.to_string(),
+            id,
+        }
+    }
+
+    /// Register a callback on a typed action
+    pub fn add_typed_action<T, F, Fut>(
+        &self,
+        hook: &ActionHook<T>,
+        handler: F,
+        priority: impl Into<Priority>,
+        plugin_id: Option<String>,
+    ) -> HookHandle
+    where
+        T: Send + Sync + 'static,
+        F: Fn(Arc<T>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let name = hook.name;
+        self.add_action(
+            name,
+            move |data: Arc<dyn Any + Send + Sync>| {
+                let run = data.downcast::<T>().ok().map(&handler);
+                if run.is_none() {
+                    tracing::warn!(
+                        hook = name,
+                        expected = std::any::type_name::<T>(),
+                        "Action fired with mismatched payload type; callback skipped"
+                    );
+                }
+                async move {
+                    if let Some(run) = run {
+                        run.await;
+                    }
+                }
+            },
+            priority,
+            plugin_id,
+        )
     }
 
     /// Execute an action hook
     pub async fn do_action(&self, name: &str, data: Arc<dyn Any + Send + Sync>) {
+        let _running = self.start_run(HookKind::Action, name);
         let action = {
             let storage = self.actions.read();
             storage.actions.get(name).cloned()
@@ -221,9 +692,19 @@ impl HookRegistry {
 
         if let Some(action) = action {
             action.execute(data).await;
+            if action.has_once() {
+                if let Some(action) = self.actions.write().actions.get_mut(name) {
+                    action.prune();
+                }
+            }
         }
     }
 
+    /// Execute a typed action hook
+    pub async fn do_typed_action<T: Send + Sync + 'static>(&self, hook: &ActionHook<T>, data: T) {
+        self.do_action(hook.name, Arc::new(data)).await
+    }
+
     /// Remove all action callbacks from a plugin
     pub fn remove_action_plugin(&self, plugin_id: &str) {
         let mut storage = self.actions.write();
@@ -246,24 +727,89 @@ impl HookRegistry {
 
     /// Register a filter hook
     pub fn add_filter<T, F, Fut>(
+        &self,
+        name: &str,
+        handler: F,
+        priority: impl Into<Priority>,
+        plugin_id: Option<String>,
+    ) -> HookHandle
+    where
+        T: Clone + Send + Sync + 'static,
+        F: Fn(T) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = T> + Send + 'static,
+    {
+        self.insert_filter(name, handler, priority.into(), plugin_id, false)
+    }
+
+    /// Register a filter callback that is removed after it first runs
+    pub fn add_filter_once<T, F, Fut>(
+        &self,
+        name: &str,
+        handler: F,
+        priority: impl Into<Priority>,
+        plugin_id: Option<String>,
+    ) -> HookHandle
+    where
+        T: Clone + Send + Sync + 'static,
+        F: Fn(T) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = T> + Send + 'static,
+    {
+        self.insert_filter(name, handler, priority.into(), plugin_id, true)
+    }
+
+    fn insert_filter<T, F, Fut>(
         &self,
         name: &str,
         handler: F,
         priority: Priority,
         plugin_id: Option<String>,
-    ) where
+        once: bool,
+    ) -> HookHandle
+    where
         T: Clone + Send + Sync + 'static,
         F: Fn(T) -> Fut + Send + Sync + 'static,
         Fut: Future<Output = T> + Send + 'static,
     {
+        let meta = CallbackMeta::new(priority, plugin_id, once);
+        let handle = HookHandle {
+            kind: HookKind::Filter,
+            name: name.to_string(),
+            id: meta.id,
+        };
+
         let mut filters = self.filters.write();
         let filter = filters
             .entry(name.to_string())
             .or_insert_with(|| Box::new(RwLock::new(Filter::<T>::new(name))));
 
-        if let Some(filter) = filter.downcast_mut::<RwLock<Filter<T>>>() {
-            filter.write().add(handler, priority, plugin_id);
+        match filter.as_any().downcast_ref::<RwLock<Filter<T>>>() {
+            Some(filter) => {
+                filter.write().insert(handler, meta);
+            }
+            None => tracing::warn!(
+                hook = name,
+                expected = filter.value_type(),
+                got = std::any::type_name::<T>(),
+                "Filter registered with mismatched value type; callback ignored"
+            ),
         }
+        handle
+    }
+
+    /// Register a callback on a typed filter
+    pub fn add_typed_filter<T, F, Fut>(
+        &self,
+        hook: &FilterHook<T>,
+        handler: F,
+        priority: impl Into<Priority>,
+        plugin_id: Option<String>,
+    ) -> HookHandle
+    where
+        T: Clone + Send + Sync + 'static,
+        F: Fn(T) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = T> + Send + 'static,
+    {
+        self.add_filter(hook.name, handler, priority, plugin_id)
     }
 
     /// Apply a filter hook
@@ -271,23 +817,33 @@ impl HookRegistry {
     where
         T: Clone + Send + Sync + 'static,
     {
+        let _running = self.start_run(HookKind::Filter, name);
         let filter = {
             let filters = self.filters.read();
             filters
                 .get(name)
-                .and_then(|f| f.downcast_ref::<RwLock<Filter<T>>>())
+                .and_then(|f| f.as_any().downcast_ref::<RwLock<Filter<T>>>())
                 .map(|f| f.read().callbacks.clone())
         };
 
-        if let Some(callbacks) = filter {
-            let mut result = data;
-            for callback in callbacks {
-                result = (callback.handler)(result).await;
+        let Some(callbacks) = filter else {
+            return data;
+        };
+        let result = run_filters(&callbacks, data).await;
+        if callbacks.iter().any(|cb| cb.meta.fired.is_some()) {
+            if let Some(filter) = self.filters.read().get(name) {
+                filter.prune();
             }
-            result
-        } else {
-            data
         }
+        result
+    }
+
+    /// Apply a typed filter hook
+    pub async fn apply_typed_filter<T>(&self, hook: &FilterHook<T>, data: T) -> T
+    where
+        T: Clone + Send + Sync + 'static,
+    {
+        self.apply_filter(hook.name, data).await
     }
 
     /// Check if a filter has any callbacks
@@ -295,10 +851,144 @@ impl HookRegistry {
         let filters = self.filters.read();
         filters
             .get(name)
-            .and_then(|f| f.downcast_ref::<RwLock<Filter<T>>>())
+            .and_then(|f| f.as_any().downcast_ref::<RwLock<Filter<T>>>())
             .map(|f| f.read().callback_count() > 0)
             .unwrap_or(false)
     }
+
+    /// Remove all filter callbacks from a plugin
+    pub fn remove_filter_plugin(&self, plugin_id: &str) {
+        let filters = self.filters.read();
+        for filter in filters.values() {
+            filter.remove_plugin(plugin_id);
+        }
+    }
+
+    /// Remove a single callback. Returns whether it was still registered
+    pub fn remove(&self, handle: &HookHandle) -> bool {
+        match handle.kind {
+            HookKind::Action => self
+                .actions
+                .write()
+                .actions
+                .get_mut(&handle.name)
+                .is_some_and(|action| action.remove(handle.id)),
+            HookKind::Filter => self
+                .filters
+                .read()
+                .get(&handle.name)
+                .is_some_and(|filter| filter.remove(handle.id)),
+        }
+    }
+
+    /// Remove every action and filter callback owned by a plugin
+    pub fn remove_plugin(&self, plugin_id: &str) {
+        self.remove_action_plugin(plugin_id);
+        self.remove_filter_plugin(plugin_id);
+    }
+
+    // === Run tracking ===
+
+    fn start_run(&self, kind: HookKind, name: &str) -> RunGuard<'_> {
+        let key = (kind, name.to_string());
+        let mut runs = self.runs.lock();
+        let entry = runs.entry(key.clone()).or_default();
+        entry.started += 1;
+        entry.in_progress += 1;
+        RunGuard {
+            runs: &self.runs,
+            key,
+        }
+    }
+
+    fn run_counts(&self, kind: HookKind, name: &str) -> (usize, usize) {
+        self.runs
+            .lock()
+            .get(&(kind, name.to_string()))
+            .map_or((0, 0), |runs| (runs.started, runs.in_progress))
+    }
+
+    /// How many times an action has been fired, including runs in
+    /// progress and runs with no callbacks
+    pub fn did_action(&self, name: &str) -> usize {
+        self.run_counts(HookKind::Action, name).0
+    }
+
+    /// How many times a filter has been applied
+    pub fn did_filter(&self, name: &str) -> usize {
+        self.run_counts(HookKind::Filter, name).0
+    }
+
+    /// Whether an action is running right now
+    pub fn doing_action(&self, name: &str) -> bool {
+        self.run_counts(HookKind::Action, name).1 > 0
+    }
+
+    /// Whether a filter is being applied right now
+    pub fn doing_filter(&self, name: &str) -> bool {
+        self.run_counts(HookKind::Filter, name).1 > 0
+    }
+
+    // === Introspection ===
+
+    /// List all registered hooks with their callbacks, sorted by kind and name
+    pub fn list_hooks(&self) -> Vec<HookInfo> {
+        let mut hooks: Vec<HookInfo> = {
+            let storage = self.actions.read();
+            storage
+                .actions
+                .iter()
+                .filter(|(_, action)| action.callback_count() > 0)
+                .map(|(name, action)| HookInfo {
+                    name: name.clone(),
+                    kind: HookKind::Action,
+                    value_type: None,
+                    callbacks: action.callbacks(),
+                })
+                .collect()
+        };
+
+        let filters = self.filters.read();
+        hooks.extend(filters.iter().filter_map(|(name, filter)| {
+            let callbacks = filter.callbacks();
+            (!callbacks.is_empty()).then(|| HookInfo {
+                name: name.clone(),
+                kind: HookKind::Filter,
+                value_type: Some(filter.value_type()),
+                callbacks,
+            })
+        }));
+
+        hooks.sort_by(|a, b| (a.kind as u8, &a.name).cmp(&(b.kind as u8, &b.name)));
+        hooks
+    }
+
+    /// Describe a single hook by name and kind
+    pub fn hook_info(&self, name: &str, kind: HookKind) -> Option<HookInfo> {
+        self.list_hooks()
+            .into_iter()
+            .find(|h| h.kind == kind && h.name == name)
+    }
+
+    /// List hooks that have at least one callback owned by the plugin
+    pub fn hooks_for_plugin(&self, plugin_id: &str) -> Vec<HookInfo> {
+        self.list_hooks()
+            .into_iter()
+            .filter(|h| {
+                h.callbacks
+                    .iter()
+                    .any(|cb| cb.plugin_id.as_deref() == Some(plugin_id))
+            })
+            .collect()
+    }
+
+    /// List hooks where callbacks from different plugins share a priority
+    pub fn conflicts(&self) -> Vec<HookInfo> {
+        self.list_hooks()
+            .into_iter()
+            .filter(|h| !h.contested_priorities().is_empty())
+            .collect()
+    }
 }
 
 impl Default for HookRegistry {
@@ -316,8 +1006,7 @@ impl Clone for Action {
                 .iter()
                 .map(|cb| ActionCallback {
                     handler: cb.handler.clone(),
-                    priority: cb.priority,
-                    plugin_id: cb.plugin_id.clone(),
+                    meta: cb.meta.clone(),
                 })
                 .collect(),
         }
@@ -328,8 +1017,7 @@ impl<T: Clone + Send + 'static> Clone for FilterCallback<T> {
     fn clone(&self) -> Self {
         Self {
             handler: self.handler.clone(),
-            priority: self.priority,
-            plugin_id: self.plugin_id.clone(),
+            meta: self.meta.clone(),
         }
     }
 }
@@ -377,6 +1065,18 @@ pub mod hooks {
     pub const FILTER_THE_CONTENT: &str = "filter_the_content";
     /// Filter: Modify user capabilities
     pub const FILTER_USER_CAPS: &str = "filter_user_caps";
+
+    /// Typed handles for the predefined filters
+    pub mod typed {
+        use crate::hook::FilterHook;
+
+        /// Post content before saving
+        pub const POST_CONTENT: FilterHook<String> = FilterHook::new(super::FILTER_POST_CONTENT);
+        /// Post title before saving
+        pub const POST_TITLE: FilterHook<String> = FilterHook::new(super::FILTER_POST_TITLE);
+        /// Rendered content
+        pub const THE_CONTENT: FilterHook<String> = FilterHook::new(super::FILTER_THE_CONTENT);
+    }
 }
 
 #[cfg(test)]
@@ -486,4 +1186,204 @@ mod tests {
         registry.do_action("test", Arc::new(())).await;
         assert_eq!(counter.load(Ordering::SeqCst), 1);
     }
+
+    #[tokio::test]
+    async fn test_registry_filter_chain_and_removal() {
+        let registry = HookRegistry::new();
+
+        registry.add_filter(
+            "the_title",
+            |s: String| async move { format!("{} [seo]", s) },
+            Priority::LOW,
+            Some("seo".to_string()),
+        );
+        registry.add_filter(
+            "the_title",
+            |s: String| async move { s.to_uppercase() },
+            Priority::HIGH,
+            Some("shout".to_string()),
+        );
+
+        let title = registry
+            .apply_filter("the_title", "hello".to_string())
+            .await;
+        assert_eq!(title, "HELLO [seo]");
+
+        registry.remove_plugin("shout");
+        let title = registry
+            .apply_filter("the_title", "hello".to_string())
+            .await;
+        assert_eq!(title, "hello [seo]");
+    }
+
+    #[test]
+    fn test_introspection() {
+        let registry = HookRegistry::new();
+
+        registry.add_action(
+            hooks::POST_SAVED,
+            |_| async {},
+            Priority::NORMAL,
+            Some("cache".to_string()),
+        );
+        registry.add_action(
+            hooks::POST_SAVED,
+            |_| async {},
+            Priority::NORMAL,
+            Some("search".to_string()),
+        );
+        registry.add_filter(
+            hooks::FILTER_THE_CONTENT,
+            |s: String| async move { s },
+            Priority::HIGH,
+            Some("search".to_string()),
+        );
+
+        let hooks = registry.list_hooks();
+        assert_eq!(hooks.len(), 2);
+        assert_eq!(hooks[0].kind, HookKind::Action);
+        assert_eq!(hooks[0].owners(), vec!["cache", "search"]);
+        assert_eq!(hooks[1].value_type, Some(std::any::type_name::<String>()));
+
+        assert_eq!(registry.hooks_for_plugin("search").len(), 2);
+        assert_eq!(registry.hooks_for_plugin("cache").len(), 1);
+
+        let conflicts = registry.conflicts();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].name, hooks::POST_SAVED);
+        assert_eq!(conflicts[0].contested_priorities(), vec![0]);
+
+        registry.remove_plugin("search");
+        assert!(registry.hooks_for_plugin("search").is_empty());
+        assert!(registry.conflicts().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_once_and_removal_by_handle() {
+        let registry = HookRegistry::new();
+        let counter = Arc::new(AtomicI32::new(0));
+
+        let c = counter.clone();
+        registry.add_action_once(
+            "init",
+            move |_| {
+                let c = c.clone();
+                async move {
+                    c.fetch_add(1, Ordering::SeqCst);
+                }
+            },
+            10,
+            None,
+        );
+        let c = counter.clone();
+        let every_time = registry.add_action(
+            "init",
+            move |_| {
+                let c = c.clone();
+                async move {
+                    c.fetch_add(10, Ordering::SeqCst);
+                }
+            },
+            5,
+            None,
+        );
+        assert!(
+            registry
+                .hook_info("init", HookKind::Action)
+                .unwrap()
+                .callbacks[0]
+                .once
+        );
+
+        registry.do_action("init", Arc::new(())).await;
+        registry.do_action("init", Arc::new(())).await;
+        assert_eq!(counter.load(Ordering::SeqCst), 21);
+        assert_eq!(
+            registry
+                .hook_info("init", HookKind::Action)
+                .unwrap()
+                .callbacks
+                .len(),
+            1
+        );
+
+        assert!(registry.remove(&every_time));
+        assert!(!registry.remove(&every_time));
+        assert!(!registry.has_action("init"));
+
+        let suffix = registry.add_filter_once(
+            "the_title",
+            |s: String| async move { s + "!" },
+            Priority::NORMAL,
+            None,
+        );
+        assert_eq!(suffix.kind(), HookKind::Filter);
+        assert_eq!(
+            registry.apply_filter("the_title", "a".to_string()).await,
+            "a!"
+        );
+        assert_eq!(
+            registry.apply_filter("the_title", "a".to_string()).await,
+            "a"
+        );
+        assert!(!registry.remove(&suffix));
+    }
+
+    #[tokio::test]
+    async fn test_typed_hooks_and_run_tracking() {
+        const SAVED: ActionHook<u32> = ActionHook::new("post_saved");
+        let registry = Arc::new(HookRegistry::new());
+        let seen = Arc::new(AtomicI32::new(0));
+
+        let inner = registry.clone();
+        let s = seen.clone();
+        registry.add_typed_action(
+            &SAVED,
+            move |id: Arc<u32>| {
+                let registry = inner.clone();
+                let s = s.clone();
+                async move {
+                    assert!(registry.doing_action("post_saved"));
+                    s.store(*id as i32, Ordering::SeqCst);
+                }
+            },
+            Priority::NORMAL,
+            None,
+        );
+        registry.add_typed_filter(
+            &hooks::typed::THE_CONTENT,
+            |s: String| async move { s.replace("--", "\u{2013}") },
+            Priority::NORMAL,
+            None,
+        );
+
+        assert_eq!(registry.did_action("post_saved"), 0);
+        registry.do_typed_action(&SAVED, 42).await;
+        assert_eq!(seen.load(Ordering::SeqCst), 42);
+        assert_eq!(registry.did_action("post_saved"), 1);
+        assert!(!registry.doing_action("post_saved"));
+
+        // A payload of the wrong type skips typed callbacks
+        registry.do_action("post_saved", Arc::new("oops")).await;
+        assert_eq!(seen.load(Ordering::SeqCst), 42);
+        assert_eq!(registry.did_action("post_saved"), 2);
+
+        let content = registry
+            .apply_typed_filter(&hooks::typed::THE_CONTENT, "a -- b".to_string())
+            .await;
+        assert_eq!(content, "a \u{2013} b");
+        assert_eq!(registry.did_filter(hooks::FILTER_THE_CONTENT), 1);
+        assert_eq!(registry.did_filter(hooks::FILTER_POST_TITLE), 0);
+    }
+
+    #[test]
+    fn test_equal_priorities_keep_registration_order() {
+        let mut filter: Filter<String> = Filter::new("order");
+        filter.add(|s| async move { s + "a" }, 10, None);
+        filter.add(|s| async move { s + "b" }, 10, None);
+        filter.add(|s| async move { s + "c" }, 20, None);
+
+        let result = futures::executor::block_on(filter.apply(String::new()));
+        assert_eq!(result, "cab");
+    }
 }
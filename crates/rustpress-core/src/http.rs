@@ -0,0 +1,619 @@
+//! Outbound HTTP client.
+//!
+//! Every call RustPress makes to another service (GA4, webhooks, oEmbed
+//! providers, email APIs) goes through one shared [`HttpClient`], which
+//! applies a policy per destination host: a request timeout, retries of
+//! transient failures within a retry budget, a circuit breaker and a cap on
+//! concurrent requests. The underlying connection pool is shared, and each
+//! destination keeps counters for [`HttpClient::metrics`].
+//!
+//! Redirects are not followed unless a caller asks for it with
+//! [`HttpClient::following_redirects`], so a URL that was checked before
+//! the request cannot hand the request on to somewhere that was not.
+
+use crate::error::{Error, Result};
+use dashmap::DashMap;
+use parking_lot::Mutex;
+use reqwest::{
+    redirect, ClientBuilder, IntoUrl, Method, Request, RequestBuilder, Response, StatusCode,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+
+/// Most retry tokens a destination can bank
+const MAX_RETRY_TOKENS: f64 = 10.0;
+
+/// Destinations tracked at once; idle ones are dropped beyond this
+const MAX_DESTINATIONS: usize = 1024;
+
+/// Redirects followed by a client that follows them
+const MAX_REDIRECTS: usize = 5;
+
+/// Limits applied to requests to one destination
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HttpPolicy {
+    /// Request timeout, unless the request sets its own
+    pub timeout_ms: u64,
+    /// Retries after the first attempt
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled for each further one
+    pub retry_backoff_ms: u64,
+    /// Also retry methods that are not idempotent, such as POST
+    pub retry_non_idempotent: bool,
+    /// Consecutive failures that open the circuit
+    pub failure_threshold: u32,
+    /// How long an open circuit rejects requests before a probe
+    pub open_secs: u64,
+    /// Requests in flight at once; further requests wait
+    pub max_concurrent: usize,
+}
+
+impl Default for HttpPolicy {
+    fn default() -> Self {
+        Self {
+            timeout_ms: 10_000,
+            max_retries: 2,
+            retry_backoff_ms: 200,
+            retry_non_idempotent: false,
+            failure_threshold: 5,
+            open_secs: 30,
+            max_concurrent: 32,
+        }
+    }
+}
+
+/// Outbound HTTP configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HttpConfig {
+    /// Policy for hosts without their own entry
+    pub default_policy: HttpPolicy,
+    /// Policies by destination host
+    pub destinations: HashMap<String, HttpPolicy>,
+    /// Retries allowed per request, averaged over time. Keeps retries from
+    /// multiplying the load on a destination that is already struggling.
+    pub retry_budget: f64,
+    /// TCP/TLS connect timeout
+    pub connect_timeout_ms: u64,
+    /// Idle connections kept per host
+    pub pool_max_idle_per_host: usize,
+    /// How long an idle connection is kept
+    pub pool_idle_timeout_secs: u64,
+    pub user_agent: String,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            default_policy: HttpPolicy::default(),
+            destinations: HashMap::new(),
+            retry_budget: 0.2,
+            connect_timeout_ms: 5_000,
+            pool_max_idle_per_host: 16,
+            pool_idle_timeout_secs: 90,
+            user_agent: format!("RustPress/{}", crate::VERSION),
+        }
+    }
+}
+
+impl HttpConfig {
+    fn policy(&self, host: &str) -> &HttpPolicy {
+        self.destinations.get(host).unwrap_or(&self.default_policy)
+    }
+}
+
+/// Circuit breaker state
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+#[derive(Debug)]
+enum Breaker {
+    Closed {
+        failures: u32,
+    },
+    Open {
+        until: Instant,
+    },
+    /// A probe request is in flight
+    HalfOpen,
+}
+
+impl Breaker {
+    /// Whether a request may go out; lets a single probe through once the
+    /// open period has passed
+    fn allow(&mut self) -> bool {
+        match self {
+            Self::Closed { .. } => true,
+            Self::Open { until } if Instant::now() >= *until => {
+                *self = Self::HalfOpen;
+                true
+            }
+            Self::Open { .. } | Self::HalfOpen => false,
+        }
+    }
+
+    fn record(&mut self, success: bool, policy: &HttpPolicy) {
+        let open = Self::Open {
+            until: Instant::now() + Duration::from_secs(policy.open_secs),
+        };
+        *self = match (&*self, success) {
+            (_, true) => Self::Closed { failures: 0 },
+            (Self::Closed { failures }, false) if failures + 1 < policy.failure_threshold => {
+                Self::Closed {
+                    failures: failures + 1,
+                }
+            }
+            (Self::Open { .. }, false) => return,
+            _ => open,
+        };
+    }
+
+    fn state(&self) -> CircuitState {
+        match self {
+            Self::Closed { .. } => CircuitState::Closed,
+            Self::Open { .. } => CircuitState::Open,
+            Self::HalfOpen => CircuitState::HalfOpen,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    requests: AtomicU64,
+    succeeded: AtomicU64,
+    failed: AtomicU64,
+    retries: AtomicU64,
+    retries_denied: AtomicU64,
+    rejected: AtomicU64,
+    latency_ms: AtomicU64,
+}
+
+/// Counters for one destination host
+#[derive(Debug, Clone, Serialize)]
+pub struct DestinationMetrics {
+    pub host: String,
+    pub circuit: CircuitState,
+    pub requests: u64,
+    pub succeeded: u64,
+    pub failed: u64,
+    pub retries: u64,
+    /// Retries skipped because the budget was spent
+    pub retries_denied: u64,
+    /// Requests refused by the open circuit
+    pub rejected: u64,
+    /// Mean attempt latency
+    pub avg_latency_ms: u64,
+}
+
+#[derive(Debug)]
+struct Destination {
+    policy: HttpPolicy,
+    breaker: Mutex<Breaker>,
+    retry_tokens: Mutex<f64>,
+    permits: Semaphore,
+    counters: Counters,
+}
+
+impl Destination {
+    fn new(policy: HttpPolicy) -> Self {
+        Self {
+            permits: Semaphore::new(policy.max_concurrent.max(1)),
+            policy,
+            breaker: Mutex::new(Breaker::Closed { failures: 0 }),
+            retry_tokens: Mutex::new(MAX_RETRY_TOKENS),
+            counters: Counters::default(),
+        }
+    }
+
+    /// Take a retry token, if the budget has one
+    fn take_retry_token(&self) -> bool {
+        let mut tokens = self.retry_tokens.lock();
+        if *tokens < 1.0 {
+            return false;
+        }
+        *tokens -= 1.0;
+        true
+    }
+
+    fn metrics(&self, host: &str) -> DestinationMetrics {
+        let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+        let attempts = load(&self.counters.requests) + load(&self.counters.retries);
+        DestinationMetrics {
+            host: host.to_string(),
+            circuit: self.breaker.lock().state(),
+            requests: load(&self.counters.requests),
+            succeeded: load(&self.counters.succeeded),
+            failed: load(&self.counters.failed),
+            retries: load(&self.counters.retries),
+            retries_denied: load(&self.counters.retries_denied),
+            rejected: load(&self.counters.rejected),
+            avg_latency_ms: load(&self.counters.latency_ms)
+                .checked_div(attempts)
+                .unwrap_or(0),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Inner {
+    client: reqwest::Client,
+    /// Same settings, but follows redirects
+    redirecting: reqwest::Client,
+    config: HttpConfig,
+    destinations: DashMap<String, Arc<Destination>>,
+}
+
+/// Shared outbound HTTP client; clone it rather than building another
+#[derive(Debug, Clone)]
+pub struct HttpClient {
+    inner: Arc<Inner>,
+    follow_redirects: bool,
+}
+
+fn client_builder(config: &HttpConfig) -> ClientBuilder {
+    reqwest::Client::builder()
+        .connect_timeout(Duration::from_millis(config.connect_timeout_ms))
+        .pool_max_idle_per_host(config.pool_max_idle_per_host)
+        .pool_idle_timeout(Duration::from_secs(config.pool_idle_timeout_secs))
+        .user_agent(config.user_agent.as_str())
+}
+
+fn build_client(builder: ClientBuilder) -> Result<reqwest::Client> {
+    builder.build().map_err(|e| Error::Configuration {
+        message: format!("Failed to build HTTP client: {}", e),
+    })
+}
+
+impl HttpClient {
+    pub fn new(config: HttpConfig) -> Result<Self> {
+        let client = build_client(client_builder(&config).redirect(redirect::Policy::none()))?;
+        let redirecting = build_client(
+            client_builder(&config).redirect(redirect::Policy::limited(MAX_REDIRECTS)),
+        )?;
+
+        Ok(Self {
+            inner: Arc::new(Inner {
+                client,
+                redirecting,
+                config,
+                destinations: DashMap::new(),
+            }),
+            follow_redirects: false,
+        })
+    }
+
+    /// The same client, following up to five redirects. Only for fixed,
+    /// trusted URLs: the hops are not checked against any allowlist, and
+    /// policies and metrics stay with the first host.
+    pub fn following_redirects(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            follow_redirects: true,
+        }
+    }
+
+    pub fn config(&self) -> &HttpConfig {
+        &self.inner.config
+    }
+
+    /// Start building a request; send it with [`HttpClient::send`]
+    pub fn request(&self, method: Method, url: impl IntoUrl) -> RequestBuilder {
+        self.inner.client.request(method, url)
+    }
+
+    pub fn get(&self, url: impl IntoUrl) -> RequestBuilder {
+        self.request(Method::GET, url)
+    }
+
+    pub fn post(&self, url: impl IntoUrl) -> RequestBuilder {
+        self.request(Method::POST, url)
+    }
+
+    /// Send a request under its destination's policy
+    ///
+    /// Connection failures, timeouts and 429/502/503/504 responses are
+    /// retried when the method allows it and the retry budget has room.
+    /// Other responses are returned as they are, whatever their status;
+    /// 5xx responses still count as failures for the circuit breaker.
+    pub async fn send(&self, request: RequestBuilder) -> Result<Response> {
+        let request = request.build().map_err(|e| network_error(None, e))?;
+        self.execute(request).await
+    }
+
+    /// Send a request that is already built, e.g. to sign it first, under
+    /// the same policy as [`HttpClient::send`]
+    pub async fn execute(&self, request: Request) -> Result<Response> {
+        let client = if self.follow_redirects {
+            &self.inner.redirecting
+        } else {
+            &self.inner.client
+        };
+        self.execute_with(client, request).await
+    }
+
+    /// Send a request connecting only to `addrs`, without following
+    /// redirects
+    ///
+    /// For URLs whose addresses were checked before the request, so a DNS
+    /// answer that changes in between cannot send it elsewhere. The request
+    /// gets a connection of its own instead of one from the shared pool.
+    pub async fn send_to(&self, request: RequestBuilder, addrs: &[SocketAddr]) -> Result<Response> {
+        let request = request.build().map_err(|e| network_error(None, e))?;
+        self.execute_to(request, addrs).await
+    }
+
+    /// Send a request that is already built, e.g. to sign it first,
+    /// connecting only to `addrs` like [`HttpClient::send_to`]
+    pub async fn execute_to(&self, request: Request, addrs: &[SocketAddr]) -> Result<Response> {
+        let host = request.url().host_str().unwrap_or_default().to_string();
+        let client = build_client(
+            client_builder(&self.inner.config)
+                .redirect(redirect::Policy::none())
+                .resolve_to_addrs(&host, addrs),
+        )?;
+        self.execute_with(&client, request).await
+    }
+
+    async fn execute_with(&self, client: &reqwest::Client, request: Request) -> Result<Response> {
+        let host = request
+            .url()
+            .host_str()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        let destination = self.destination(&host);
+        let policy = &destination.policy;
+        let counters = &destination.counters;
+
+        if !destination.breaker.lock().allow() {
+            counters.rejected.fetch_add(1, Ordering::Relaxed);
+            return Err(Error::ServiceUnavailable { service: host });
+        }
+        counters.requests.fetch_add(1, Ordering::Relaxed);
+        {
+            let mut tokens = destination.retry_tokens.lock();
+            *tokens = (*tokens + self.inner.config.retry_budget).min(MAX_RETRY_TOKENS);
+        }
+
+        let _permit = destination.permits.acquire().await;
+        let retryable_method = policy.retry_non_idempotent || is_idempotent(request.method());
+        let mut request = request;
+        let mut attempt = 0;
+
+        loop {
+            // Streaming bodies cannot be replayed
+            let retry_copy = request.try_clone();
+            let mut current = request;
+            if current.timeout().is_none() {
+                *current.timeout_mut() = Some(Duration::from_millis(policy.timeout_ms));
+            }
+
+            let started = Instant::now();
+            let result = client.execute(current).await;
+            counters
+                .latency_ms
+                .fetch_add(started.elapsed().as_millis() as u64, Ordering::Relaxed);
+
+            let transient = match &result {
+                Ok(response) => is_retryable_status(response.status()),
+                Err(e) => e.is_connect() || e.is_timeout(),
+            };
+            if transient && retryable_method && attempt < policy.max_retries {
+                if let Some(copy) = retry_copy {
+                    if destination.take_retry_token() {
+                        attempt += 1;
+                        counters.retries.fetch_add(1, Ordering::Relaxed);
+                        let backoff = policy.retry_backoff_ms.saturating_mul(1 << (attempt - 1));
+                        tokio::time::sleep(Duration::from_millis(backoff)).await;
+                        request = copy;
+                        continue;
+                    }
+                    counters.retries_denied.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+
+            let success = match &result {
+                Ok(response) => !response.status().is_server_error(),
+                Err(_) => false,
+            };
+            destination.breaker.lock().record(success, policy);
+            if success {
+                counters.succeeded.fetch_add(1, Ordering::Relaxed);
+            } else {
+                counters.failed.fetch_add(1, Ordering::Relaxed);
+            }
+
+            return result.map_err(|e| network_error(Some(&host), e));
+        }
+    }
+
+    /// Counters for every destination contacted so far
+    pub fn metrics(&self) -> Vec<DestinationMetrics> {
+        let mut metrics: Vec<_> = self
+            .inner
+            .destinations
+            .iter()
+            .map(|entry| entry.value().metrics(entry.key()))
+            .collect();
+        metrics.sort_by(|a, b| a.host.cmp(&b.host));
+        metrics
+    }
+
+    fn destination(&self, host: &str) -> Arc<Destination> {
+        let destinations = &self.inner.destinations;
+        if let Some(destination) = destinations.get(host) {
+            return destination.clone();
+        }
+        let new = || Arc::new(Destination::new(self.inner.config.policy(host).clone()));
+
+        // Hosts come from user-supplied URLs too, so forget the ones with
+        // nothing in flight once the map is full
+        if destinations.len() >= MAX_DESTINATIONS {
+            destinations.retain(|host, destination| {
+                self.inner.config.destinations.contains_key(host)
+                    || Arc::strong_count(destination) > 1
+            });
+            if destinations.len() >= MAX_DESTINATIONS {
+                return new();
+            }
+        }
+        destinations
+            .entry(host.to_string())
+            .or_insert_with(new)
+            .clone()
+    }
+}
+
+impl Default for HttpClient {
+    fn default() -> Self {
+        Self::new(HttpConfig::default()).expect("default HTTP client configuration is valid")
+    }
+}
+
+fn is_idempotent(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::PUT | Method::DELETE
+    )
+}
+
+fn is_retryable_status(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::TOO_MANY_REQUESTS
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+fn network_error(host: Option<&str>, e: reqwest::Error) -> Error {
+    // URLs can carry credentials in their query string
+    let e = e.without_url();
+    let message = match host {
+        Some(host) => format!("Request to {} failed: {}", host, e),
+        None => format!("Invalid request: {}", e),
+    };
+    Error::Network {
+        message,
+        source: Some(Box::new(e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> HttpPolicy {
+        HttpPolicy {
+            failure_threshold: 2,
+            open_secs: 60,
+            ..HttpPolicy::default()
+        }
+    }
+
+    #[test]
+    fn test_breaker_opens_and_probes() {
+        let policy = policy();
+        let mut breaker = Breaker::Closed { failures: 0 };
+
+        breaker.record(false, &policy);
+        assert!(breaker.allow());
+        breaker.record(false, &policy);
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert!(!breaker.allow());
+
+        breaker = Breaker::Open {
+            until: Instant::now(),
+        };
+        assert!(breaker.allow());
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        // Only the one probe goes out
+        assert!(!breaker.allow());
+
+        breaker.record(true, &policy);
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[test]
+    fn test_retry_budget() {
+        let destination = Destination::new(policy());
+        for _ in 0..10 {
+            assert!(destination.take_retry_token());
+        }
+        assert!(!destination.take_retry_token());
+    }
+
+    #[test]
+    fn test_destinations_are_bounded() {
+        let client = HttpClient::default();
+        let busy = client.destination("busy.example");
+        for i in 0..MAX_DESTINATIONS + 10 {
+            client.destination(&format!("{}.example", i));
+        }
+
+        assert!(client.inner.destinations.len() <= MAX_DESTINATIONS);
+        // Destinations with requests in flight are kept
+        assert!(Arc::ptr_eq(&busy, &client.destination("busy.example")));
+    }
+
+    #[test]
+    fn test_destination_policy() {
+        let mut config = HttpConfig::default();
+        config.destinations.insert(
+            "analyticsdata.googleapis.com".to_string(),
+            HttpPolicy {
+                timeout_ms: 30_000,
+                ..HttpPolicy::default()
+            },
+        );
+
+        assert_eq!(
+            config.policy("analyticsdata.googleapis.com").timeout_ms,
+            30_000
+        );
+        assert_eq!(config.policy("example.com").timeout_ms, 10_000);
+    }
+
+    #[tokio::test]
+    async fn test_send_retries_then_opens_circuit() {
+        let config = HttpConfig {
+            default_policy: HttpPolicy {
+                retry_backoff_ms: 1,
+                ..policy()
+            },
+            ..HttpConfig::default()
+        };
+        let client = HttpClient::new(config).unwrap();
+        // Nothing listens on port 1, so every attempt is refused
+        let url = "http://127.0.0.1:1/";
+
+        assert!(matches!(
+            client.send(client.get(url)).await,
+            Err(Error::Network { .. })
+        ));
+        assert!(client.send(client.get(url)).await.is_err());
+        assert!(matches!(
+            client.send(client.get(url)).await,
+            Err(Error::ServiceUnavailable { .. })
+        ));
+
+        let metrics = &client.metrics()[0];
+        assert_eq!(metrics.host, "127.0.0.1");
+        assert_eq!(metrics.circuit, CircuitState::Open);
+        assert_eq!(metrics.requests, 2);
+        assert_eq!(metrics.retries, 4);
+        assert_eq!(metrics.failed, 2);
+        assert_eq!(metrics.rejected, 1);
+    }
+}
@@ -0,0 +1,179 @@
+//! Validation of JSON values against a subset of JSON Schema.
+//!
+//! Supports `type`, `enum`, `const`, `minimum`/`maximum`, `required`,
+//! `properties`, `additionalProperties: false`, `items` and
+//! `if`/`then`/`else`, which covers the schemas plugins declare for their
+//! settings and publishers declare for event payloads. Other keywords are
+//! ignored.
+
+use crate::error::ValidationErrors;
+use serde_json::{Map, Value};
+
+/// Validate `value` against `schema`, reporting problems under `path`
+pub fn validate(schema: &Value, value: &Value, path: &str) -> Result<(), ValidationErrors> {
+    let mut errors = ValidationErrors::new();
+    validate_value(schema, value, path, &mut errors);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Validate `value` against `schema`, adding problems to `errors`
+pub fn validate_value(schema: &Value, value: &Value, path: &str, errors: &mut ValidationErrors) {
+    let Some(schema) = schema.as_object() else {
+        return;
+    };
+
+    if let Some(expected) = schema.get("type") {
+        let allowed: Vec<&str> = match expected {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(types) => types.iter().filter_map(|t| t.as_str()).collect(),
+            _ => Vec::new(),
+        };
+        if !allowed.is_empty() && !allowed.iter().any(|t| matches_type(t, value)) {
+            errors.add_with_code(
+                path,
+                format!("expected {}, got {}", allowed.join(" | "), type_name(value)),
+                "type",
+            );
+            return;
+        }
+    }
+
+    if let Some(Value::Array(options)) = schema.get("enum") {
+        if !options.contains(value) {
+            errors.add_with_code(path, "value is not one of the allowed options", "enum");
+        }
+    }
+
+    if let Some(expected) = schema.get("const") {
+        if expected != value {
+            errors.add_with_code(path, format!("must be {}", expected), "const");
+        }
+    }
+
+    if let Some(n) = value.as_f64() {
+        if let Some(min) = schema.get("minimum").and_then(Value::as_f64) {
+            if n < min {
+                errors.add_with_code(path, format!("must be >= {}", min), "minimum");
+            }
+        }
+        if let Some(max) = schema.get("maximum").and_then(Value::as_f64) {
+            if n > max {
+                errors.add_with_code(path, format!("must be <= {}", max), "maximum");
+            }
+        }
+    }
+
+    if let Value::Object(object) = value {
+        if let Some(Value::Array(required)) = schema.get("required") {
+            for field in required.iter().filter_map(Value::as_str) {
+                if !object.contains_key(field) {
+                    errors.add_with_code(format!("{}.{}", path, field), "is required", "required");
+                }
+            }
+        }
+
+        let properties = schema.get("properties").and_then(Value::as_object);
+        for (key, child) in object {
+            let child_path = format!("{}.{}", path, key);
+            match properties.and_then(|p| p.get(key)) {
+                Some(child_schema) => validate_value(child_schema, child, &child_path, errors),
+                None => {
+                    if schema.get("additionalProperties") == Some(&Value::Bool(false)) {
+                        errors.add_with_code(child_path, "is not allowed", "additional_properties");
+                    }
+                }
+            }
+        }
+    }
+
+    if let (Value::Array(items), Some(item_schema)) = (value, schema.get("items")) {
+        for (i, item) in items.iter().enumerate() {
+            validate_value(item_schema, item, &format!("{}[{}]", path, i), errors);
+        }
+    }
+
+    if let Some(condition) = schema.get("if") {
+        let branch = if validate(condition, value, path).is_ok() {
+            schema.get("then")
+        } else {
+            schema.get("else")
+        };
+        if let Some(branch) = branch {
+            validate_value(branch, value, path, errors);
+        }
+    }
+}
+
+/// Fill in the `default` of each top-level property missing from `value`.
+/// Values that aren't objects are returned unchanged
+pub fn apply_defaults(schema: &Value, value: Value) -> Value {
+    let Value::Object(mut object) = value else {
+        return value;
+    };
+    let properties = schema
+        .get("properties")
+        .and_then(Value::as_object)
+        .cloned()
+        .unwrap_or_else(Map::new);
+
+    for (key, property) in properties {
+        if let Some(default) = property.get("default") {
+            object.entry(key).or_insert_with(|| default.clone());
+        }
+    }
+    Value::Object(object)
+}
+
+fn matches_type(expected: &str, value: &Value) -> bool {
+    match expected {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "boolean" => value.is_boolean(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_conditional_required() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "demo_mode": {"type": "boolean", "default": false},
+                "property_id": {"type": "string"}
+            },
+            "if": {"properties": {"demo_mode": {"const": true}}, "required": ["demo_mode"]},
+            "else": {"required": ["property_id"]}
+        });
+
+        assert!(validate(&schema, &json!({"demo_mode": true}), "settings").is_ok());
+        let errors = validate(&schema, &json!({"demo_mode": false}), "settings").unwrap_err();
+        assert_eq!(errors.errors[0].field, "settings.property_id");
+
+        let filled = apply_defaults(&schema, json!({"property_id": "123"}));
+        assert_eq!(filled, json!({"demo_mode": false, "property_id": "123"}));
+    }
+}
@@ -4,34 +4,49 @@
 //! This crate defines all shared abstractions used across the system.
 
 pub mod api;
+pub mod compat;
 pub mod config;
+pub mod config_bundle;
 pub mod context;
 pub mod discovery;
 pub mod error;
+pub mod fault;
 pub mod health;
 pub mod hook;
+pub mod http;
 pub mod id;
+pub mod json_schema;
 pub mod middleware;
 pub mod plugin;
 pub mod plugin_loader;
+pub mod plugin_settings;
 pub mod repository;
 pub mod service;
 pub mod tenant;
 pub mod types;
 
 // Re-exports for convenience
+pub use compat::{CompatProfile, CompatReport, Platform};
 pub use config::AppConfig;
 pub use context::{AppContext, RequestContext};
 pub use discovery::{
     ComponentManifest, ComponentType, DiscoveryConfig, DiscoveryService, DiscoverySource,
 };
 pub use error::{Error, Result};
-pub use hook::{Action, Filter, Hook, HookRegistry};
+pub use fault::{FaultInjector, FaultRule, FaultTarget};
+pub use hook::{
+    Action, ActionHook, Filter, FilterHook, Hook, HookHandle, HookInfo, HookKind, HookRegistry,
+};
+pub use http::{HttpClient, HttpConfig, HttpPolicy};
 pub use id::TenantId;
 pub use id::{EntityId, Id};
-pub use plugin::{Plugin, PluginInfo, PluginManager};
+pub use plugin::{
+    Plugin, PluginDataExporter, PluginExport, PluginInfo, PluginManager, PluginResources,
+    ResourceKind,
+};
 pub use plugin_loader::{LoadResult, PluginLoader, PluginManifest};
-pub use tenant::Tenant;
+pub use plugin_settings::{PluginSettings, PluginSettingsStore, StoredSettings};
+pub use tenant::{ExtensionAllowlist, ExtensionKind, MeteredResource, Tenant, UsageMeter};
 
 /// The current version of RustPress
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -1,14 +1,26 @@
 //! Plugin system for RustPress using trait objects and dynamic dispatch.
 //!
 //! Allows extending functionality through a WordPress-like plugin architecture.
+//!
+//! Plugins are activated and deactivated at runtime. Activation resolves
+//! the plugin's dependencies, runs its migrations when its version changed
+//! and lets it register [`PluginResources`] such as routes, jobs and event
+//! subscribers, which are torn down again when it is deactivated.
+//! Before activating, it is handed its saved [`PluginSettings`].
 
+use crate::compat::{self, CompatProfile, CompatReport, Platform};
 use crate::context::AppContext;
 use crate::error::Result;
+use crate::hook::HookRegistry;
+use crate::plugin_settings::{PluginSettings, StoredSettings};
+use crate::tenant::ExtensionKind;
 use async_trait::async_trait;
-use parking_lot::RwLock;
-use semver::Version;
+use futures::future::BoxFuture;
+use parking_lot::{Mutex, RwLock};
+use semver::{Version, VersionReq};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
+use std::future::Future;
 use std::sync::Arc;
 
 /// Metadata about a plugin
@@ -34,6 +46,9 @@ pub struct PluginInfo {
     pub dependencies: Vec<PluginDependency>,
     /// Minimum RustPress version required
     pub min_rustpress_version: Option<Version>,
+    /// Platform features required, see [`compat::features`]
+    #[serde(default)]
+    pub required_features: Vec<String>,
     /// Tags for categorization
     pub tags: Vec<String>,
 }
@@ -51,6 +66,7 @@ impl PluginInfo {
             license: "MIT".to_string(),
             dependencies: Vec::new(),
             min_rustpress_version: None,
+            required_features: Vec::new(),
             tags: Vec::new(),
         }
     }
@@ -69,6 +85,26 @@ impl PluginInfo {
         self.dependencies.push(dep);
         self
     }
+
+    pub fn with_min_rustpress_version(mut self, version: Version) -> Self {
+        self.min_rustpress_version = Some(version);
+        self
+    }
+
+    pub fn with_required_feature(mut self, feature: impl Into<String>) -> Self {
+        self.required_features.push(feature.into());
+        self
+    }
+
+    /// Compatibility profile with the plugin's version and feature
+    /// requirements
+    pub fn compat_profile(&self) -> CompatProfile {
+        let mut profile =
+            CompatProfile::new(ExtensionKind::Plugin, &self.id, self.version.to_string());
+        profile.min_rustpress_version = self.min_rustpress_version.clone();
+        profile.required_features = self.required_features.clone();
+        profile
+    }
 }
 
 /// A dependency on another plugin
@@ -112,6 +148,139 @@ pub enum PluginState {
     Error,
 }
 
+/// Plugin data carried in a site export bundle
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PluginExport {
+    /// ID of the plugin that owns the data
+    pub plugin_id: String,
+    /// Version of the payload layout; importers upgrade older payloads
+    pub schema_version: u32,
+    /// Plugin-defined payload, without secrets
+    pub data: serde_json::Value,
+}
+
+/// Exports and imports a plugin's data as part of site export bundles
+///
+/// Payloads are versioned: bump [`schema_version`](Self::schema_version)
+/// whenever the layout of `data` changes, and accept every older version
+/// in [`import`](Self::import). Bundles written by a newer schema are
+/// rejected before `import` is called.
+#[async_trait]
+pub trait PluginDataExporter: Send + Sync {
+    /// ID of the plugin the data belongs to
+    fn plugin_id(&self) -> &str;
+
+    /// Current payload layout version
+    fn schema_version(&self) -> u32;
+
+    /// Data to include in the bundle, or `None` if there is nothing to
+    /// export. Secrets such as API keys must be left out.
+    async fn export(&self) -> Result<Option<serde_json::Value>>;
+
+    /// Restore data written with `schema_version`, which is never newer
+    /// than the current one
+    async fn import(&self, schema_version: u32, data: serde_json::Value) -> Result<()>;
+}
+
+/// Kind of resource a plugin registers while active
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResourceKind {
+    Route,
+    Job,
+    Subscriber,
+    Hook,
+    Other,
+}
+
+/// A resource registered by an active plugin
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PluginResource {
+    pub kind: ResourceKind,
+    pub name: String,
+}
+
+type Teardown = Box<dyn FnOnce() -> BoxFuture<'static, Result<()>> + Send>;
+
+/// Resources a plugin registers on activation, with the teardown that
+/// removes each of them again
+///
+/// Teardowns run in reverse registration order when the plugin is
+/// deactivated, or when its activation fails part way.
+pub struct PluginResources {
+    plugin_id: String,
+    entries: Mutex<Vec<(PluginResource, Teardown)>>,
+}
+
+impl PluginResources {
+    fn new(plugin_id: &str) -> Self {
+        Self {
+            plugin_id: plugin_id.to_string(),
+            entries: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// ID of the plugin owning the resources
+    pub fn plugin_id(&self) -> &str {
+        &self.plugin_id
+    }
+
+    /// Record a resource and how to tear it down
+    pub fn add<F, Fut>(&self, kind: ResourceKind, name: impl Into<String>, teardown: F)
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let resource = PluginResource {
+            kind,
+            name: name.into(),
+        };
+        self.entries
+            .lock()
+            .push((resource, Box::new(move || Box::pin(teardown()))));
+    }
+
+    /// Registered resources, in registration order
+    pub fn list(&self) -> Vec<PluginResource> {
+        self.entries.lock().iter().map(|(r, _)| r.clone()).collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.lock().is_empty()
+    }
+
+    /// Run every teardown, newest first. Failures are logged and don't stop
+    /// the remaining teardowns; the first one is returned
+    async fn teardown(&self) -> Result<()> {
+        let entries = std::mem::take(&mut *self.entries.lock());
+        let mut first_error = None;
+
+        for (resource, teardown) in entries.into_iter().rev() {
+            if let Err(e) = teardown().await {
+                tracing::warn!(
+                    plugin_id = %self.plugin_id,
+                    kind = ?resource.kind,
+                    resource = %resource.name,
+                    error = %e,
+                    "Plugin resource teardown failed"
+                );
+                first_error.get_or_insert(e);
+            }
+        }
+
+        first_error.map_or(Ok(()), Err)
+    }
+}
+
+impl std::fmt::Debug for PluginResources {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PluginResources")
+            .field("plugin_id", &self.plugin_id)
+            .field("resources", &self.list())
+            .finish()
+    }
+}
+
 /// The main Plugin trait that all plugins must implement
 #[async_trait]
 pub trait Plugin: Send + Sync {
@@ -124,6 +293,30 @@ pub trait Plugin: Send + Sync {
     /// Called when the plugin is deactivated
     async fn deactivate(&self, ctx: &AppContext) -> Result<()>;
 
+    /// Called on activation before [`activate`](Self::activate) when the
+    /// plugin's version differs from the last one migrated, with that
+    /// version, or `None` on first activation
+    async fn migrate(&self, _ctx: &AppContext, _from: Option<&Version>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called with the plugin's installation-wide settings before
+    /// [`activate`](Self::activate), and again whenever they are saved
+    /// while it is active
+    async fn load_settings(&self, _ctx: &AppContext, _settings: &serde_json::Value) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called after [`activate`](Self::activate) to register the routes,
+    /// jobs and subscribers to tear down on deactivation
+    async fn register_resources(
+        &self,
+        _ctx: &AppContext,
+        _resources: &PluginResources,
+    ) -> Result<()> {
+        Ok(())
+    }
+
     /// Called during application startup (after all plugins are loaded)
     async fn on_startup(&self, _ctx: &AppContext) -> Result<()> {
         Ok(())
@@ -148,6 +341,18 @@ pub trait Plugin: Send + Sync {
     fn state(&self) -> PluginState {
         PluginState::Inactive
     }
+
+    /// Data this plugin contributes to site export bundles (if any)
+    fn data_exporter(&self) -> Option<Arc<dyn PluginDataExporter>> {
+        None
+    }
+
+    /// What the plugin requires and claims, checked before activation.
+    /// Override to declare the routes and hooks it registers and the
+    /// plugins it conflicts with
+    fn compat_profile(&self) -> CompatProfile {
+        self.info().compat_profile()
+    }
 }
 
 /// A registered plugin with its runtime state
@@ -155,12 +360,21 @@ struct RegisteredPlugin {
     plugin: Arc<dyn Plugin>,
     state: PluginState,
     error: Option<String>,
+    resources: Arc<PluginResources>,
 }
 
 /// Manages all registered plugins
 pub struct PluginManager {
     plugins: RwLock<HashMap<String, RegisteredPlugin>>,
     load_order: RwLock<Vec<String>>,
+    /// Plugins that may be activated; `None` allows all
+    allowlist: RwLock<Option<BTreeSet<String>>>,
+    /// Version each plugin was last migrated to
+    installed_versions: RwLock<HashMap<String, Version>>,
+    /// Platform plugins are checked against before activation
+    platform: RwLock<Platform>,
+    /// Where plugin settings are kept
+    settings: RwLock<Arc<PluginSettings>>,
 }
 
 impl PluginManager {
@@ -168,7 +382,153 @@ impl PluginManager {
         Self {
             plugins: RwLock::new(HashMap::new()),
             load_order: RwLock::new(Vec::new()),
+            allowlist: RwLock::new(None),
+            installed_versions: RwLock::new(HashMap::new()),
+            platform: RwLock::new(Platform::current()),
+            settings: RwLock::new(Arc::new(PluginSettings::in_memory())),
+        }
+    }
+
+    /// Set where plugin settings are kept; in memory until set
+    pub fn set_settings(&self, settings: Arc<PluginSettings>) {
+        *self.settings.write() = settings;
+    }
+
+    pub fn settings(&self) -> Arc<PluginSettings> {
+        self.settings.read().clone()
+    }
+
+    /// Saved settings of a plugin, with its schema's defaults filled in
+    pub async fn plugin_settings(
+        &self,
+        plugin_id: &str,
+        tenant_id: Option<&str>,
+    ) -> Result<StoredSettings> {
+        let plugin = self
+            .get(plugin_id)
+            .ok_or_else(|| crate::error::Error::PluginNotFound {
+                plugin_id: plugin_id.to_string(),
+            })?;
+        self.settings()
+            .load(plugin_id, tenant_id, plugin.config_schema().as_ref())
+            .await
+    }
+
+    /// Validate and save a plugin's settings over `expected_version`. An
+    /// active plugin is handed its new installation-wide settings
+    pub async fn update_settings(
+        &self,
+        plugin_id: &str,
+        tenant_id: Option<&str>,
+        values: serde_json::Value,
+        expected_version: i64,
+        ctx: &AppContext,
+    ) -> Result<StoredSettings> {
+        let plugin = self
+            .get(plugin_id)
+            .ok_or_else(|| crate::error::Error::PluginNotFound {
+                plugin_id: plugin_id.to_string(),
+            })?;
+        let schema = plugin.config_schema();
+        let settings = self.settings();
+
+        let saved = settings
+            .save(
+                plugin_id,
+                tenant_id,
+                values,
+                schema.as_ref(),
+                expected_version,
+            )
+            .await?;
+
+        if tenant_id.is_none() && self.state(plugin_id) == Some(PluginState::Active) {
+            let loaded = settings.load(plugin_id, None, schema.as_ref()).await?;
+            plugin.load_settings(ctx, &loaded.values).await?;
         }
+        Ok(saved)
+    }
+
+    /// Set the platform plugins are checked against, with the features
+    /// the installation provides
+    pub fn set_platform(&self, platform: Platform) {
+        *self.platform.write() = platform;
+    }
+
+    pub fn platform(&self) -> Platform {
+        self.platform.read().clone()
+    }
+
+    /// Compatibility profiles of the active plugins
+    pub fn active_profiles(&self) -> Vec<CompatProfile> {
+        let plugins = self.plugins.read();
+        self.load_order
+            .read()
+            .iter()
+            .filter_map(|id| plugins.get(id))
+            .filter(|r| r.state == PluginState::Active)
+            .map(|r| r.plugin.compat_profile())
+            .collect()
+    }
+
+    /// Check a plugin against the platform and the active plugins
+    pub fn check_compatibility(&self, plugin_id: &str) -> Result<CompatReport> {
+        let plugin = self
+            .get(plugin_id)
+            .ok_or_else(|| crate::error::Error::PluginNotFound {
+                plugin_id: plugin_id.to_string(),
+            })?;
+        Ok(compat::check(
+            &self.platform.read(),
+            &plugin.compat_profile(),
+            &self.active_profiles(),
+        ))
+    }
+
+    /// Check every registered plugin, in load order
+    pub fn audit_compatibility(&self) -> Vec<CompatReport> {
+        let profiles: Vec<CompatProfile> = {
+            let plugins = self.plugins.read();
+            self.load_order
+                .read()
+                .iter()
+                .filter_map(|id| plugins.get(id))
+                .map(|r| r.plugin.compat_profile())
+                .collect()
+        };
+        compat::audit(&self.platform.read(), &profiles, &self.active_profiles())
+    }
+
+    /// Version a plugin was last migrated to
+    pub fn installed_version(&self, plugin_id: &str) -> Option<Version> {
+        self.installed_versions.read().get(plugin_id).cloned()
+    }
+
+    /// Restore the version a plugin was migrated to, e.g. from storage at
+    /// startup, so activation only migrates from there
+    pub fn set_installed_version(&self, plugin_id: &str, version: Version) {
+        self.installed_versions
+            .write()
+            .insert(plugin_id.to_string(), version);
+    }
+
+    /// Restrict which plugins may be activated. Plugins already active
+    /// stay active.
+    pub fn set_allowlist(&self, allowlist: Option<BTreeSet<String>>) {
+        *self.allowlist.write() = allowlist;
+    }
+
+    /// Plugins that may be activated, `None` when unrestricted
+    pub fn allowlist(&self) -> Option<BTreeSet<String>> {
+        self.allowlist.read().clone()
+    }
+
+    /// Whether a plugin may be activated
+    pub fn is_allowed(&self, plugin_id: &str) -> bool {
+        self.allowlist
+            .read()
+            .as_ref()
+            .is_none_or(|ids| ids.contains(plugin_id))
     }
 
     /// Register a plugin
@@ -190,6 +550,7 @@ impl PluginManager {
                 plugin,
                 state: PluginState::Inactive,
                 error: None,
+                resources: Arc::new(PluginResources::new(&id)),
             },
         );
 
@@ -219,13 +580,27 @@ impl PluginManager {
         Ok(())
     }
 
-    /// Activate a plugin
+    /// Activate a plugin. Its required dependencies must already be active
+    /// in a matching version; [`activate_with_dependencies`](Self::activate_with_dependencies)
+    /// activates them first. Activating an active plugin does nothing.
     pub async fn activate(&self, plugin_id: &str, ctx: &AppContext) -> Result<()> {
-        // Check dependencies first
+        if !self.is_allowed(plugin_id) {
+            return Err(crate::error::Error::extension_not_allowed(
+                "Plugin",
+                plugin_id,
+                "this network",
+            ));
+        }
+        if self.state(plugin_id) == Some(PluginState::Active) {
+            return Ok(());
+        }
+
+        // Check dependencies and compatibility first
         self.check_dependencies(plugin_id)?;
+        self.check_compatibility(plugin_id)?.into_result()?;
 
-        // Update state to activating
-        {
+        // Update state to activating and get the plugin
+        let (plugin, resources) = {
             let mut plugins = self.plugins.write();
             let registered =
                 plugins
@@ -234,28 +609,25 @@ impl PluginManager {
                         plugin_id: plugin_id.to_string(),
                     })?;
             registered.state = PluginState::Activating;
-        }
-
-        // Get plugin reference
-        let plugin = {
-            let plugins = self.plugins.read();
-            plugins.get(plugin_id).map(|r| r.plugin.clone())
-        }
-        .ok_or_else(|| crate::error::Error::PluginNotFound {
-            plugin_id: plugin_id.to_string(),
-        })?;
+            (registered.plugin.clone(), registered.resources.clone())
+        };
 
         // Activate
-        match plugin.activate(ctx).await {
+        match self.start(plugin.as_ref(), &resources, ctx).await {
             Ok(()) => {
                 let mut plugins = self.plugins.write();
                 if let Some(registered) = plugins.get_mut(plugin_id) {
                     registered.state = PluginState::Active;
                     registered.error = None;
                 }
+                tracing::info!(plugin_id = %plugin_id, "Plugin activated");
                 Ok(())
             }
             Err(e) => {
+                // Tear down whatever the plugin registered before failing
+                let _ = resources.teardown().await;
+                remove_plugin_hooks(plugin_id, ctx);
+
                 let mut plugins = self.plugins.write();
                 if let Some(registered) = plugins.get_mut(plugin_id) {
                     registered.state = PluginState::Error;
@@ -266,13 +638,96 @@ impl PluginManager {
         }
     }
 
-    /// Deactivate a plugin
+    /// Migrate the plugin if its version changed, activate it and let it
+    /// register its resources
+    async fn start(
+        &self,
+        plugin: &dyn Plugin,
+        resources: &PluginResources,
+        ctx: &AppContext,
+    ) -> Result<()> {
+        let info = plugin.info();
+        let installed = self.installed_version(&info.id);
+        if installed.as_ref() != Some(&info.version) {
+            tracing::info!(
+                plugin_id = %info.id,
+                from = ?installed.as_ref().map(Version::to_string),
+                to = %info.version,
+                "Migrating plugin"
+            );
+            plugin.migrate(ctx, installed.as_ref()).await?;
+            self.set_installed_version(&info.id, info.version.clone());
+        }
+
+        let settings = self
+            .settings()
+            .load(&info.id, None, plugin.config_schema().as_ref())
+            .await?;
+        plugin.load_settings(ctx, &settings.values).await?;
+
+        plugin.activate(ctx).await?;
+
+        if let Err(e) = plugin.register_resources(ctx, resources).await {
+            if let Err(deactivate_error) = plugin.deactivate(ctx).await {
+                tracing::warn!(
+                    plugin_id = %info.id,
+                    error = %deactivate_error,
+                    "Plugin deactivation after failed resource registration failed"
+                );
+            }
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    /// Activate a plugin after the dependencies it requires, in dependency
+    /// order. If one of them fails, the plugins activated so far are
+    /// deactivated again. Returns the plugins that were activated
+    pub async fn activate_with_dependencies(
+        &self,
+        plugin_id: &str,
+        ctx: &AppContext,
+    ) -> Result<Vec<String>> {
+        let mut activated: Vec<String> = Vec::new();
+
+        for id in self.activation_order(plugin_id)? {
+            if self.state(&id) == Some(PluginState::Active) {
+                continue;
+            }
+            if let Err(e) = self.activate(&id, ctx).await {
+                for id in activated.iter().rev() {
+                    if let Err(rollback_error) = self.deactivate(id, ctx).await {
+                        tracing::warn!(
+                            plugin_id = %id,
+                            error = %rollback_error,
+                            "Plugin rollback failed"
+                        );
+                    }
+                }
+                return Err(e);
+            }
+            activated.push(id);
+        }
+
+        Ok(activated)
+    }
+
+    /// Deactivate a plugin and tear down the resources it registered, and
+    /// the hooks it added to the context's [`HookRegistry`]. Fails while an
+    /// active plugin requires it; [`deactivate_with_dependents`](Self::deactivate_with_dependents)
+    /// deactivates those first. Teardown failures are logged and don't fail
+    /// the deactivation.
     pub async fn deactivate(&self, plugin_id: &str, ctx: &AppContext) -> Result<()> {
+        if self.state(plugin_id) == Some(PluginState::Inactive) {
+            return Ok(());
+        }
+
         // Check if other plugins depend on this one
         self.check_dependents(plugin_id)?;
 
-        // Update state
-        {
+        // Update state and get the plugin
+        let (plugin, resources) = {
             let mut plugins = self.plugins.write();
             let registered =
                 plugins
@@ -281,24 +736,22 @@ impl PluginManager {
                         plugin_id: plugin_id.to_string(),
                     })?;
             registered.state = PluginState::Deactivating;
-        }
+            (registered.plugin.clone(), registered.resources.clone())
+        };
 
-        // Get plugin reference
-        let plugin = {
-            let plugins = self.plugins.read();
-            plugins.get(plugin_id).map(|r| r.plugin.clone())
-        }
-        .ok_or_else(|| crate::error::Error::PluginNotFound {
-            plugin_id: plugin_id.to_string(),
-        })?;
+        // Deactivate, then tear down what the plugin registered
+        let result = plugin.deactivate(ctx).await;
+        let _ = resources.teardown().await;
+        remove_plugin_hooks(plugin_id, ctx);
 
-        // Deactivate
-        match plugin.deactivate(ctx).await {
+        match result {
             Ok(()) => {
                 let mut plugins = self.plugins.write();
                 if let Some(registered) = plugins.get_mut(plugin_id) {
                     registered.state = PluginState::Inactive;
+                    registered.error = None;
                 }
+                tracing::info!(plugin_id = %plugin_id, "Plugin deactivated");
                 Ok(())
             }
             Err(e) => {
@@ -312,6 +765,37 @@ impl PluginManager {
         }
     }
 
+    /// Deactivate a plugin after the active plugins requiring it, directly
+    /// or through other plugins. Returns the plugins that were deactivated
+    pub async fn deactivate_with_dependents(
+        &self,
+        plugin_id: &str,
+        ctx: &AppContext,
+    ) -> Result<Vec<String>> {
+        let order = self.deactivation_order(plugin_id)?;
+        for id in &order {
+            self.deactivate(id, ctx).await?;
+        }
+        Ok(order)
+    }
+
+    /// Resources an active plugin registered
+    pub fn resources(&self, plugin_id: &str) -> Vec<PluginResource> {
+        self.plugins
+            .read()
+            .get(plugin_id)
+            .map(|r| r.resources.list())
+            .unwrap_or_default()
+    }
+
+    /// Error of a plugin whose activation or deactivation failed
+    pub fn error(&self, plugin_id: &str) -> Option<String> {
+        self.plugins
+            .read()
+            .get(plugin_id)
+            .and_then(|r| r.error.clone())
+    }
+
     /// Get a plugin by ID
     pub fn get(&self, plugin_id: &str) -> Option<Arc<dyn Plugin>> {
         self.plugins.read().get(plugin_id).map(|r| r.plugin.clone())
@@ -331,6 +815,18 @@ impl PluginManager {
             .collect()
     }
 
+    /// Data exporters of active plugins, in load order
+    pub fn data_exporters(&self) -> Vec<Arc<dyn PluginDataExporter>> {
+        let plugins = self.plugins.read();
+        self.load_order
+            .read()
+            .iter()
+            .filter_map(|id| plugins.get(id))
+            .filter(|r| r.state == PluginState::Active)
+            .filter_map(|r| r.plugin.data_exporter())
+            .collect()
+    }
+
     /// List active plugins
     pub fn list_active(&self) -> Vec<PluginInfo> {
         self.plugins
@@ -341,6 +837,44 @@ impl PluginManager {
             .collect()
     }
 
+    /// Plugins to activate for `plugin_id`, its required dependencies first
+    /// and the plugin itself last. Fails when a dependency is missing or
+    /// doesn't match the required version, or on a dependency cycle
+    pub fn activation_order(&self, plugin_id: &str) -> Result<Vec<String>> {
+        let plugins = self.plugins.read();
+        if !plugins.contains_key(plugin_id) {
+            return Err(crate::error::Error::PluginNotFound {
+                plugin_id: plugin_id.to_string(),
+            });
+        }
+
+        let mut order = Vec::new();
+        visit_dependencies(&plugins, plugin_id, &mut Vec::new(), &mut order)?;
+        Ok(order)
+    }
+
+    /// Plugins to deactivate for `plugin_id`, the active plugins requiring
+    /// it first and the plugin itself last
+    pub fn deactivation_order(&self, plugin_id: &str) -> Result<Vec<String>> {
+        let plugins = self.plugins.read();
+        if !plugins.contains_key(plugin_id) {
+            return Err(crate::error::Error::PluginNotFound {
+                plugin_id: plugin_id.to_string(),
+            });
+        }
+
+        let load_order = self.load_order.read();
+        let mut order = Vec::new();
+        visit_dependents(
+            &plugins,
+            &load_order,
+            plugin_id,
+            &mut BTreeSet::new(),
+            &mut order,
+        );
+        Ok(order)
+    }
+
     /// Check if a plugin's dependencies are satisfied
     fn check_dependencies(&self, plugin_id: &str) -> Result<()> {
         let plugins = self.plugins.read();
@@ -370,7 +904,7 @@ impl PluginManager {
                         dependency: format!("{} (not active)", dep.plugin_id),
                     });
                 }
-                _ => {}
+                Some(p) => check_dependency_version(plugin_id, dep, p.plugin.info())?,
             }
         }
 
@@ -448,6 +982,115 @@ impl PluginManager {
     }
 }
 
+/// Check that a dependency's version matches the requirement on it
+fn check_dependency_version(
+    plugin_id: &str,
+    dep: &PluginDependency,
+    dependency: &PluginInfo,
+) -> Result<()> {
+    if dep.version_req.trim().is_empty() {
+        return Ok(());
+    }
+
+    let req = VersionReq::parse(&dep.version_req).map_err(|e| crate::error::Error::Plugin {
+        plugin_id: plugin_id.to_string(),
+        message: format!(
+            "Invalid version requirement '{}' on {}: {}",
+            dep.version_req, dep.plugin_id, e
+        ),
+    })?;
+
+    if !req.matches(&dependency.version) {
+        return Err(crate::error::Error::PluginDependency {
+            plugin_id: plugin_id.to_string(),
+            dependency: format!(
+                "{} {} (found {})",
+                dep.plugin_id, dep.version_req, dependency.version
+            ),
+        });
+    }
+
+    Ok(())
+}
+
+/// Depth-first walk of required dependencies, adding each plugin after
+/// the ones it requires
+fn visit_dependencies(
+    plugins: &HashMap<String, RegisteredPlugin>,
+    plugin_id: &str,
+    path: &mut Vec<String>,
+    order: &mut Vec<String>,
+) -> Result<()> {
+    if order.iter().any(|id| id == plugin_id) {
+        return Ok(());
+    }
+    if path.iter().any(|id| id == plugin_id) {
+        return Err(crate::error::Error::Plugin {
+            plugin_id: plugin_id.to_string(),
+            message: format!("Dependency cycle: {} -> {}", path.join(" -> "), plugin_id),
+        });
+    }
+
+    path.push(plugin_id.to_string());
+    for dep in &plugins[plugin_id].plugin.info().dependencies {
+        if dep.optional {
+            continue;
+        }
+        let dependency =
+            plugins
+                .get(&dep.plugin_id)
+                .ok_or_else(|| crate::error::Error::PluginDependency {
+                    plugin_id: plugin_id.to_string(),
+                    dependency: dep.plugin_id.clone(),
+                })?;
+        check_dependency_version(plugin_id, dep, dependency.plugin.info())?;
+        visit_dependencies(plugins, &dep.plugin_id, path, order)?;
+    }
+    path.pop();
+
+    order.push(plugin_id.to_string());
+    Ok(())
+}
+
+/// Depth-first walk of active plugins requiring `plugin_id`, adding each
+/// plugin after the ones requiring it
+fn visit_dependents(
+    plugins: &HashMap<String, RegisteredPlugin>,
+    load_order: &[String],
+    plugin_id: &str,
+    visited: &mut BTreeSet<String>,
+    order: &mut Vec<String>,
+) {
+    if !visited.insert(plugin_id.to_string()) {
+        return;
+    }
+
+    for id in load_order {
+        let Some(registered) = plugins.get(id) else {
+            continue;
+        };
+        let requires = registered
+            .plugin
+            .info()
+            .dependencies
+            .iter()
+            .any(|dep| dep.plugin_id == plugin_id && !dep.optional);
+        if registered.state == PluginState::Active && requires {
+            visit_dependents(plugins, load_order, id, visited, order);
+        }
+    }
+
+    order.push(plugin_id.to_string());
+}
+
+/// Remove the hook callbacks a plugin added, when the context has a
+/// [`HookRegistry`]
+fn remove_plugin_hooks(plugin_id: &str, ctx: &AppContext) {
+    if let Some(hooks) = ctx.get::<HookRegistry>() {
+        hooks.remove_plugin(plugin_id);
+    }
+}
+
 impl Default for PluginManager {
     fn default() -> Self {
         Self::new()
@@ -460,14 +1103,44 @@ mod tests {
 
     struct TestPlugin {
         info: PluginInfo,
+        exporter: Option<Arc<dyn PluginDataExporter>>,
     }
 
     impl TestPlugin {
         fn new(id: &str) -> Self {
             Self {
                 info: PluginInfo::new(id, id, Version::new(1, 0, 0)),
+                exporter: None,
             }
         }
+
+        fn exporting(id: &str) -> Self {
+            Self {
+                exporter: Some(Arc::new(TestExporter(id.to_string()))),
+                ..Self::new(id)
+            }
+        }
+    }
+
+    struct TestExporter(String);
+
+    #[async_trait]
+    impl PluginDataExporter for TestExporter {
+        fn plugin_id(&self) -> &str {
+            &self.0
+        }
+
+        fn schema_version(&self) -> u32 {
+            1
+        }
+
+        async fn export(&self) -> Result<Option<serde_json::Value>> {
+            Ok(Some(serde_json::json!({})))
+        }
+
+        async fn import(&self, _schema_version: u32, _data: serde_json::Value) -> Result<()> {
+            Ok(())
+        }
     }
 
     #[async_trait]
@@ -483,6 +1156,10 @@ mod tests {
         async fn deactivate(&self, _ctx: &AppContext) -> Result<()> {
             Ok(())
         }
+
+        fn data_exporter(&self) -> Option<Arc<dyn PluginDataExporter>> {
+            self.exporter.clone()
+        }
     }
 
     #[test]
@@ -517,4 +1194,432 @@ mod tests {
 
         assert_eq!(manager.state("test-plugin"), Some(PluginState::Active));
     }
+
+    #[tokio::test]
+    async fn test_plugin_allowlist() {
+        let manager = PluginManager::new();
+        let ctx = AppContext::new(crate::config::AppConfig::default());
+        manager.register(Arc::new(TestPlugin::new("seo"))).unwrap();
+        manager
+            .register(Arc::new(TestPlugin::new("forms")))
+            .unwrap();
+        manager.set_allowlist(Some(BTreeSet::from(["seo".to_string()])));
+
+        manager.activate("seo", &ctx).await.unwrap();
+        let err = manager.activate("forms", &ctx).await.unwrap_err();
+        assert_eq!(err.error_code(), "EXTENSION_NOT_ALLOWED");
+        assert_eq!(manager.state("forms"), Some(PluginState::Inactive));
+    }
+
+    #[tokio::test]
+    async fn test_data_exporters_of_active_plugins() {
+        let manager = PluginManager::new();
+        let ctx = AppContext::new(crate::config::AppConfig::default());
+        manager
+            .register(Arc::new(TestPlugin::exporting("analytics")))
+            .unwrap();
+        manager
+            .register(Arc::new(TestPlugin::exporting("forms")))
+            .unwrap();
+        manager.register(Arc::new(TestPlugin::new("seo"))).unwrap();
+        assert!(manager.data_exporters().is_empty());
+
+        for id in ["analytics", "seo"] {
+            manager.activate(id, &ctx).await.unwrap();
+        }
+        let exporters = manager.data_exporters();
+        assert_eq!(exporters.len(), 1);
+        assert_eq!(exporters[0].plugin_id(), "analytics");
+    }
+
+    #[tokio::test]
+    async fn test_incompatible_plugin_is_not_activated() {
+        let manager = PluginManager::new();
+        let ctx = AppContext::new(crate::config::AppConfig::default());
+        let mut plugin = TestPlugin::new("semantic-search");
+        plugin.info = plugin
+            .info
+            .with_required_feature(compat::features::PGVECTOR);
+        manager.register(Arc::new(plugin)).unwrap();
+
+        let err = manager.activate("semantic-search", &ctx).await.unwrap_err();
+        assert_eq!(err.error_code(), "INCOMPATIBLE");
+        assert_eq!(
+            manager.state("semantic-search"),
+            Some(PluginState::Inactive)
+        );
+        assert!(!manager.audit_compatibility()[0].is_compatible());
+
+        manager.set_platform(Platform::current().with_feature(compat::features::PGVECTOR));
+        manager.activate("semantic-search", &ctx).await.unwrap();
+        assert_eq!(manager.active_profiles().len(), 1);
+    }
+
+    /// Plugin recording its lifecycle calls in a shared log
+    struct LifecyclePlugin {
+        info: PluginInfo,
+        log: Arc<Mutex<Vec<String>>>,
+        fail_activation: bool,
+    }
+
+    impl LifecyclePlugin {
+        fn new(id: &str, version: Version, log: &Arc<Mutex<Vec<String>>>) -> Self {
+            Self {
+                info: PluginInfo::new(id, id, version),
+                log: log.clone(),
+                fail_activation: false,
+            }
+        }
+
+        fn requires(mut self, plugin_id: &str, version_req: &str) -> Self {
+            self.info
+                .dependencies
+                .push(PluginDependency::new(plugin_id, version_req));
+            self
+        }
+    }
+
+    #[async_trait]
+    impl Plugin for LifecyclePlugin {
+        fn info(&self) -> &PluginInfo {
+            &self.info
+        }
+
+        async fn migrate(&self, _ctx: &AppContext, from: Option<&Version>) -> Result<()> {
+            let from = from.map_or("none".to_string(), Version::to_string);
+            self.log
+                .lock()
+                .push(format!("migrate {} {}", self.info.id, from));
+            Ok(())
+        }
+
+        async fn activate(&self, _ctx: &AppContext) -> Result<()> {
+            self.log.lock().push(format!("activate {}", self.info.id));
+            Ok(())
+        }
+
+        async fn register_resources(
+            &self,
+            _ctx: &AppContext,
+            resources: &PluginResources,
+        ) -> Result<()> {
+            for (kind, name) in [(ResourceKind::Route, "routes"), (ResourceKind::Job, "jobs")] {
+                let log = self.log.clone();
+                let id = self.info.id.clone();
+                resources.add(kind, name, move || async move {
+                    log.lock().push(format!("teardown {} {}", id, name));
+                    Ok(())
+                });
+            }
+            if self.fail_activation {
+                return Err(crate::error::Error::Plugin {
+                    plugin_id: self.info.id.clone(),
+                    message: "subscriber failed".to_string(),
+                });
+            }
+            Ok(())
+        }
+
+        async fn deactivate(&self, _ctx: &AppContext) -> Result<()> {
+            self.log.lock().push(format!("deactivate {}", self.info.id));
+            Ok(())
+        }
+    }
+
+    fn lifecycle_manager(log: &Arc<Mutex<Vec<String>>>) -> PluginManager {
+        let manager = PluginManager::new();
+        let plugins = [
+            LifecyclePlugin::new("shop", Version::new(2, 0, 0), log)
+                .requires("payments", "^1.2")
+                .requires("forms", "*"),
+            LifecyclePlugin::new("payments", Version::new(1, 4, 0), log).requires("forms", ""),
+            LifecyclePlugin::new("forms", Version::new(3, 1, 0), log),
+        ];
+        for plugin in plugins {
+            manager.register(Arc::new(plugin)).unwrap();
+        }
+        manager
+    }
+
+    #[tokio::test]
+    async fn test_activate_with_dependencies() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let manager = lifecycle_manager(&log);
+        let ctx = AppContext::new(crate::config::AppConfig::default());
+
+        let err = manager.activate("shop", &ctx).await.unwrap_err();
+        assert_eq!(err.error_code(), "PLUGIN_DEPENDENCY");
+
+        assert_eq!(
+            manager
+                .activate_with_dependencies("shop", &ctx)
+                .await
+                .unwrap(),
+            vec!["forms", "payments", "shop"]
+        );
+        assert_eq!(manager.state("payments"), Some(PluginState::Active));
+        assert_eq!(manager.resources("shop").len(), 2);
+        assert_eq!(
+            manager
+                .activate_with_dependencies("shop", &ctx)
+                .await
+                .unwrap(),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn test_dependency_resolution_errors() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let manager = PluginManager::new();
+        manager
+            .register(Arc::new(
+                LifecyclePlugin::new("shop", Version::new(1, 0, 0), &log)
+                    .requires("payments", "^2"),
+            ))
+            .unwrap();
+        assert!(
+            manager.activation_order("shop").is_err(),
+            "missing dependency"
+        );
+
+        manager
+            .register(Arc::new(
+                LifecyclePlugin::new("payments", Version::new(1, 4, 0), &log).requires("shop", "*"),
+            ))
+            .unwrap();
+        let err = manager.activation_order("shop").unwrap_err();
+        assert!(err.to_string().contains("found 1.4.0"), "{}", err);
+
+        manager.unregister("payments").unwrap();
+        manager
+            .register(Arc::new(
+                LifecyclePlugin::new("payments", Version::new(2, 0, 0), &log).requires("shop", "*"),
+            ))
+            .unwrap();
+        let err = manager.activation_order("shop").unwrap_err();
+        assert!(
+            err.to_string().contains("shop -> payments -> shop"),
+            "{}",
+            err
+        );
+    }
+
+    #[tokio::test]
+    async fn test_deactivate_tears_down_resources() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let manager = lifecycle_manager(&log);
+        let ctx = AppContext::new(crate::config::AppConfig::default());
+        let hooks = HookRegistry::new();
+        hooks.add_action(
+            "init",
+            |_| async {},
+            crate::hook::Priority::NORMAL,
+            Some("payments".to_string()),
+        );
+        ctx.register(hooks);
+        manager
+            .activate_with_dependencies("shop", &ctx)
+            .await
+            .unwrap();
+        log.lock().clear();
+
+        assert!(manager.deactivate("payments", &ctx).await.is_err());
+        assert_eq!(
+            manager
+                .deactivate_with_dependents("forms", &ctx)
+                .await
+                .unwrap(),
+            vec!["shop", "payments", "forms"]
+        );
+        assert_eq!(
+            log.lock()[..6],
+            [
+                "deactivate shop",
+                "teardown shop jobs",
+                "teardown shop routes",
+                "deactivate payments",
+                "teardown payments jobs",
+                "teardown payments routes",
+            ]
+        );
+        assert!(manager.resources("shop").is_empty());
+        assert!(!ctx.get::<HookRegistry>().unwrap().has_action("init"));
+    }
+
+    #[tokio::test]
+    async fn test_migrations_run_on_version_change() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let manager = PluginManager::new();
+        let ctx = AppContext::new(crate::config::AppConfig::default());
+        manager
+            .register(Arc::new(LifecyclePlugin::new(
+                "forms",
+                Version::new(3, 1, 0),
+                &log,
+            )))
+            .unwrap();
+        manager.set_installed_version("forms", Version::new(3, 0, 0));
+
+        manager.activate("forms", &ctx).await.unwrap();
+        manager.deactivate("forms", &ctx).await.unwrap();
+        manager.activate("forms", &ctx).await.unwrap();
+
+        let migrations: Vec<String> = log
+            .lock()
+            .iter()
+            .filter(|entry| entry.starts_with("migrate"))
+            .cloned()
+            .collect();
+        assert_eq!(migrations, vec!["migrate forms 3.0.0"]);
+        assert_eq!(
+            manager.installed_version("forms"),
+            Some(Version::new(3, 1, 0))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_activation_rolls_back() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let manager = PluginManager::new();
+        let ctx = AppContext::new(crate::config::AppConfig::default());
+        let mut broken =
+            LifecyclePlugin::new("shop", Version::new(1, 0, 0), &log).requires("forms", "^3");
+        broken.fail_activation = true;
+        manager.register(Arc::new(broken)).unwrap();
+        manager
+            .register(Arc::new(LifecyclePlugin::new(
+                "forms",
+                Version::new(3, 1, 0),
+                &log,
+            )))
+            .unwrap();
+
+        assert!(manager
+            .activate_with_dependencies("shop", &ctx)
+            .await
+            .is_err());
+        assert_eq!(manager.state("shop"), Some(PluginState::Error));
+        assert_eq!(
+            manager.error("shop").unwrap(),
+            "Plugin error: shop - subscriber failed"
+        );
+        assert_eq!(manager.state("forms"), Some(PluginState::Inactive));
+        assert!(log.lock().contains(&"teardown shop routes".to_string()));
+    }
+
+    /// Plugin keeping the settings it was last handed
+    struct SettingsPlugin {
+        info: PluginInfo,
+        loaded: Mutex<Vec<serde_json::Value>>,
+    }
+
+    #[async_trait]
+    impl Plugin for SettingsPlugin {
+        fn info(&self) -> &PluginInfo {
+            &self.info
+        }
+
+        fn config_schema(&self) -> Option<serde_json::Value> {
+            Some(serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "api_key": {"type": "string"},
+                    "sample_rate": {"type": "number", "maximum": 1, "default": 1}
+                }
+            }))
+        }
+
+        async fn load_settings(
+            &self,
+            _ctx: &AppContext,
+            settings: &serde_json::Value,
+        ) -> Result<()> {
+            self.loaded.lock().push(settings.clone());
+            Ok(())
+        }
+
+        async fn activate(&self, _ctx: &AppContext) -> Result<()> {
+            Ok(())
+        }
+
+        async fn deactivate(&self, _ctx: &AppContext) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_settings_loaded_on_activation() {
+        let manager = PluginManager::new();
+        let ctx = AppContext::new(crate::config::AppConfig::default());
+        let plugin = Arc::new(SettingsPlugin {
+            info: PluginInfo::new("metrics", "Metrics", Version::new(1, 0, 0)),
+            loaded: Mutex::new(Vec::new()),
+        });
+        manager.register(plugin.clone()).unwrap();
+
+        let err = manager
+            .update_settings(
+                "metrics",
+                None,
+                serde_json::json!({"sample_rate": 2}),
+                0,
+                &ctx,
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(err.error_code(), "VALIDATION_ERROR");
+        manager
+            .update_settings(
+                "metrics",
+                None,
+                serde_json::json!({"api_key": "k1"}),
+                0,
+                &ctx,
+            )
+            .await
+            .unwrap();
+        assert!(
+            plugin.loaded.lock().is_empty(),
+            "inactive plugins aren't handed settings"
+        );
+
+        manager.activate("metrics", &ctx).await.unwrap();
+        assert_eq!(
+            plugin.loaded.lock().last().unwrap(),
+            &serde_json::json!({"api_key": "k1", "sample_rate": 1})
+        );
+
+        let mut changes = manager.settings().subscribe();
+        manager
+            .update_settings(
+                "metrics",
+                None,
+                serde_json::json!({"api_key": "k2"}),
+                1,
+                &ctx,
+            )
+            .await
+            .unwrap();
+        assert_eq!(plugin.loaded.lock().last().unwrap()["api_key"], "k2");
+        assert_eq!(changes.try_recv().unwrap().version, 2);
+
+        // Tenant settings are stored apart and don't touch the running plugin
+        manager
+            .update_settings(
+                "metrics",
+                Some("acme"),
+                serde_json::json!({"api_key": "t1"}),
+                0,
+                &ctx,
+            )
+            .await
+            .unwrap();
+        assert_eq!(plugin.loaded.lock().len(), 2);
+        let tenant = manager
+            .plugin_settings("metrics", Some("acme"))
+            .await
+            .unwrap();
+        assert_eq!(tenant.values["api_key"], "t1");
+    }
 }
@@ -0,0 +1,287 @@
+//! Persistent plugin settings.
+//!
+//! Settings are stored per plugin and per tenant, where no tenant means the
+//! settings of the whole installation. They are validated against the
+//! plugin's [`config_schema`](crate::plugin::Plugin::config_schema) before
+//! they are saved and carry a version that every save increments, so two
+//! admins editing at once can't silently overwrite each other. The
+//! [`PluginManager`](crate::plugin::PluginManager) hands a plugin its
+//! settings when activating it, and subscribers hear about every change.
+
+use crate::error::{Error, Result};
+use crate::json_schema;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+/// Settings of one plugin for one tenant
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredSettings {
+    pub plugin_id: String,
+    /// `None` for the installation-wide settings
+    pub tenant_id: Option<String>,
+    /// Incremented by every save; 0 before the first
+    pub version: i64,
+    pub values: Value,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl StoredSettings {
+    /// Settings that have never been saved
+    pub fn empty(plugin_id: &str, tenant_id: Option<&str>) -> Self {
+        Self {
+            plugin_id: plugin_id.to_string(),
+            tenant_id: tenant_id.map(str::to_string),
+            version: 0,
+            values: Value::Object(Default::default()),
+            updated_at: None,
+        }
+    }
+}
+
+/// Where plugin settings are kept
+#[async_trait]
+pub trait PluginSettingsStore: Send + Sync {
+    /// Load the saved settings, if any
+    async fn load(
+        &self,
+        plugin_id: &str,
+        tenant_id: Option<&str>,
+    ) -> Result<Option<StoredSettings>>;
+
+    /// Save `values` as version `expected_version + 1`. Fails with
+    /// [`Error::VersionConflict`] when the stored version is not
+    /// `expected_version`
+    async fn save(
+        &self,
+        plugin_id: &str,
+        tenant_id: Option<&str>,
+        values: Value,
+        expected_version: i64,
+    ) -> Result<StoredSettings>;
+
+    /// Remove the saved settings of a plugin for every tenant
+    async fn delete(&self, plugin_id: &str) -> Result<()>;
+}
+
+/// In-memory store, for tests and installations without a database
+#[derive(Default)]
+pub struct MemorySettingsStore {
+    entries: RwLock<HashMap<(String, Option<String>), StoredSettings>>,
+}
+
+impl MemorySettingsStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl PluginSettingsStore for MemorySettingsStore {
+    async fn load(
+        &self,
+        plugin_id: &str,
+        tenant_id: Option<&str>,
+    ) -> Result<Option<StoredSettings>> {
+        let key = (plugin_id.to_string(), tenant_id.map(str::to_string));
+        Ok(self.entries.read().get(&key).cloned())
+    }
+
+    async fn save(
+        &self,
+        plugin_id: &str,
+        tenant_id: Option<&str>,
+        values: Value,
+        expected_version: i64,
+    ) -> Result<StoredSettings> {
+        let key = (plugin_id.to_string(), tenant_id.map(str::to_string));
+        let mut entries = self.entries.write();
+
+        let current = entries.get(&key).map_or(0, |s| s.version);
+        if current != expected_version {
+            return Err(Error::version_conflict(
+                "PluginSettings",
+                plugin_id,
+                current,
+            ));
+        }
+
+        let saved = StoredSettings {
+            plugin_id: plugin_id.to_string(),
+            tenant_id: tenant_id.map(str::to_string),
+            version: current + 1,
+            values,
+            updated_at: Some(Utc::now()),
+        };
+        entries.insert(key, saved.clone());
+        Ok(saved)
+    }
+
+    async fn delete(&self, plugin_id: &str) -> Result<()> {
+        self.entries.write().retain(|(id, _), _| id != plugin_id);
+        Ok(())
+    }
+}
+
+/// A saved change to a plugin's settings
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SettingsChange {
+    pub plugin_id: String,
+    pub tenant_id: Option<String>,
+    pub version: i64,
+    pub values: Value,
+}
+
+/// Validated, versioned access to plugin settings with change
+/// notifications
+pub struct PluginSettings {
+    store: Arc<dyn PluginSettingsStore>,
+    changes: broadcast::Sender<SettingsChange>,
+}
+
+impl PluginSettings {
+    pub fn new(store: Arc<dyn PluginSettingsStore>) -> Self {
+        let (changes, _) = broadcast::channel(64);
+        Self { store, changes }
+    }
+
+    /// Settings kept in memory only
+    pub fn in_memory() -> Self {
+        Self::new(Arc::new(MemorySettingsStore::new()))
+    }
+
+    /// Load a plugin's settings, with the schema's defaults filled in for
+    /// properties that were never saved
+    pub async fn load(
+        &self,
+        plugin_id: &str,
+        tenant_id: Option<&str>,
+        schema: Option<&Value>,
+    ) -> Result<StoredSettings> {
+        let mut settings = self
+            .store
+            .load(plugin_id, tenant_id)
+            .await?
+            .unwrap_or_else(|| StoredSettings::empty(plugin_id, tenant_id));
+        if let Some(schema) = schema {
+            settings.values = json_schema::apply_defaults(schema, settings.values);
+        }
+        Ok(settings)
+    }
+
+    /// Validate `values` against the schema and save them over
+    /// `expected_version`, notifying subscribers
+    pub async fn save(
+        &self,
+        plugin_id: &str,
+        tenant_id: Option<&str>,
+        values: Value,
+        schema: Option<&Value>,
+        expected_version: i64,
+    ) -> Result<StoredSettings> {
+        if let Some(schema) = schema {
+            json_schema::validate(schema, &values, "settings")?;
+        }
+
+        let saved = self
+            .store
+            .save(plugin_id, tenant_id, values, expected_version)
+            .await?;
+
+        let _ = self.changes.send(SettingsChange {
+            plugin_id: saved.plugin_id.clone(),
+            tenant_id: saved.tenant_id.clone(),
+            version: saved.version,
+            values: saved.values.clone(),
+        });
+        tracing::info!(
+            plugin_id = %plugin_id,
+            tenant_id = ?tenant_id,
+            version = saved.version,
+            "Plugin settings saved"
+        );
+        Ok(saved)
+    }
+
+    /// Remove a plugin's settings for every tenant
+    pub async fn delete(&self, plugin_id: &str) -> Result<()> {
+        self.store.delete(plugin_id).await
+    }
+
+    /// Receive every saved change
+    pub fn subscribe(&self) -> broadcast::Receiver<SettingsChange> {
+        self.changes.subscribe()
+    }
+}
+
+impl Default for PluginSettings {
+    fn default() -> Self {
+        Self::in_memory()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "enabled": {"type": "boolean", "default": true},
+                "ttl": {"type": "integer", "minimum": 1}
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn test_save_validates_and_versions() {
+        let settings = PluginSettings::in_memory();
+        let mut changes = settings.subscribe();
+
+        let loaded = settings.load("cache", None, Some(&schema())).await.unwrap();
+        assert_eq!(loaded.version, 0);
+        assert_eq!(loaded.values, json!({"enabled": true}));
+
+        let err = settings
+            .save("cache", None, json!({"ttl": 0}), Some(&schema()), 0)
+            .await
+            .unwrap_err();
+        assert_eq!(err.error_code(), "VALIDATION_ERROR");
+
+        let saved = settings
+            .save("cache", None, json!({"ttl": 60}), Some(&schema()), 0)
+            .await
+            .unwrap();
+        assert_eq!(saved.version, 1);
+        assert_eq!(changes.try_recv().unwrap().values, json!({"ttl": 60}));
+
+        // A save based on an outdated version is refused
+        let err = settings
+            .save("cache", None, json!({"ttl": 30}), Some(&schema()), 0)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::VersionConflict {
+                current_version: 1,
+                ..
+            }
+        ));
+
+        // Tenants have their own settings
+        let tenant = settings
+            .load("cache", Some("acme"), Some(&schema()))
+            .await
+            .unwrap();
+        assert_eq!(tenant.version, 0);
+        let installation = settings.load("cache", None, Some(&schema())).await.unwrap();
+        assert_eq!(installation.values, json!({"enabled": true, "ttl": 60}));
+    }
+}
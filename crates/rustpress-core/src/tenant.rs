@@ -1,11 +1,56 @@
 //! Multi-tenancy support for RustPress.
 //!
 //! Enables SaaS deployments with isolated tenant data.
+//!
+//! In network mode every request is served for one site of the network.
+//! The site is resolved once per request and scoped to the task serving
+//! it, so repositories keep their rows to that site without it being
+//! passed through every call.
 
+use crate::error::{Error, Result};
 use crate::id::TenantId;
+use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
+use std::future::Future;
+use uuid::Uuid;
+
+tokio::task_local! {
+    /// Site of the network the current task is serving
+    static SITE: Option<Uuid>;
+}
+
+/// Run `future` on behalf of a site; `None` is the main site
+pub async fn with_site<F: Future>(site: Option<Uuid>, future: F) -> F::Output {
+    SITE.scope(site, future).await
+}
+
+/// Site the current task serves; `None` for the main site and outside
+/// of requests
+pub fn current_site() -> Option<Uuid> {
+    SITE.try_with(|site| *site).ok().flatten()
+}
+
+/// Spawn `future` on the site the current task serves
+///
+/// Spawned tasks don't inherit the task-local site, so work started by a
+/// request is spawned through here to stay on the request's site.
+pub fn spawn<F>(future: F) -> tokio::task::JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    tokio::spawn(with_site(current_site(), future))
+}
+
+/// SQL condition keeping `column` to the rows of the current site
+pub fn site_filter(column: &str) -> String {
+    match current_site() {
+        Some(id) => format!("{} = '{}'", column, id),
+        None => format!("{} IS NULL", column),
+    }
+}
 
 /// Tenant status
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -41,6 +86,29 @@ impl Default for TenantPlan {
     }
 }
 
+impl TenantPlan {
+    /// Plan by name; names other than the tiers are custom plans
+    pub fn parse(name: &str) -> Self {
+        match name {
+            "free" => Self::Free,
+            "starter" => Self::Starter,
+            "professional" => Self::Professional,
+            "enterprise" => Self::Enterprise,
+            other => Self::Custom(other.to_string()),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Free => "free",
+            Self::Starter => "starter",
+            Self::Professional => "professional",
+            Self::Enterprise => "enterprise",
+            Self::Custom(name) => name,
+        }
+    }
+}
+
 /// Tenant entity
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Tenant {
@@ -158,7 +226,7 @@ pub struct TenantBranding {
 }
 
 /// Tenant quotas
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TenantQuotas {
     /// Maximum number of users
     pub max_users: Option<u32>,
@@ -174,6 +242,12 @@ pub struct TenantQuotas {
     pub max_api_requests_per_day: Option<u32>,
     /// Maximum plugins
     pub max_plugins: Option<u32>,
+    /// Maximum bytes served per calendar month
+    #[serde(default)]
+    pub max_bandwidth_bytes_per_month: Option<u64>,
+    /// Maximum minutes of background jobs per calendar month
+    #[serde(default)]
+    pub max_job_minutes_per_month: Option<u32>,
 }
 
 impl Default for TenantQuotas {
@@ -186,6 +260,8 @@ impl Default for TenantQuotas {
             max_media: Some(500),
             max_api_requests_per_day: Some(10000),
             max_plugins: Some(10),
+            max_bandwidth_bytes_per_month: Some(10 * 1024 * 1024 * 1024), // 10GB
+            max_job_minutes_per_month: Some(300),
         }
     }
 }
@@ -200,6 +276,8 @@ impl TenantQuotas {
             max_media: None,
             max_api_requests_per_day: None,
             max_plugins: None,
+            max_bandwidth_bytes_per_month: None,
+            max_job_minutes_per_month: None,
         }
     }
 
@@ -212,6 +290,8 @@ impl TenantQuotas {
             max_media: Some(50),
             max_api_requests_per_day: Some(1000),
             max_plugins: Some(3),
+            max_bandwidth_bytes_per_month: Some(1024 * 1024 * 1024), // 1GB
+            max_job_minutes_per_month: Some(60),
         }
     }
 
@@ -224,6 +304,8 @@ impl TenantQuotas {
             max_media: Some(1000),
             max_api_requests_per_day: Some(50000),
             max_plugins: Some(10),
+            max_bandwidth_bytes_per_month: Some(50 * 1024 * 1024 * 1024), // 50GB
+            max_job_minutes_per_month: Some(600),
         }
     }
 
@@ -236,12 +318,36 @@ impl TenantQuotas {
             max_media: Some(10000),
             max_api_requests_per_day: Some(500000),
             max_plugins: Some(50),
+            max_bandwidth_bytes_per_month: Some(500 * 1024 * 1024 * 1024), // 500GB
+            max_job_minutes_per_month: Some(6000),
         }
     }
 
     pub fn enterprise_tier() -> Self {
         Self::unlimited()
     }
+
+    /// Quotas of a plan; custom plans are unlimited unless given quotas
+    /// of their own
+    pub fn for_plan(plan: &TenantPlan) -> Self {
+        match plan {
+            TenantPlan::Free => Self::free_tier(),
+            TenantPlan::Starter => Self::starter_tier(),
+            TenantPlan::Professional => Self::professional_tier(),
+            TenantPlan::Enterprise => Self::enterprise_tier(),
+            TenantPlan::Custom(_) => Self::unlimited(),
+        }
+    }
+
+    /// Limit on a metered resource in its unit of metering
+    pub fn limit(&self, resource: MeteredResource) -> Option<u64> {
+        match resource {
+            MeteredResource::Storage => self.max_storage_bytes,
+            MeteredResource::Media => self.max_media.map(u64::from),
+            MeteredResource::Bandwidth => self.max_bandwidth_bytes_per_month,
+            MeteredResource::JobTime => self.max_job_minutes_per_month.map(|m| u64::from(m) * 60),
+        }
+    }
 }
 
 /// Tenant usage statistics
@@ -254,6 +360,10 @@ pub struct TenantUsage {
     pub media_count: u32,
     pub api_requests_today: u32,
     pub plugin_count: u32,
+    #[serde(default)]
+    pub bandwidth_bytes_this_month: u64,
+    #[serde(default)]
+    pub job_seconds_this_month: u64,
 }
 
 impl TenantUsage {
@@ -268,6 +378,14 @@ impl TenantUsage {
             && within(self.media_count, quotas.max_media)
             && within(self.api_requests_today, quotas.max_api_requests_per_day)
             && within(self.plugin_count, quotas.max_plugins)
+            && within_storage(
+                self.bandwidth_bytes_this_month,
+                quotas.max_bandwidth_bytes_per_month,
+            )
+            && within_storage(
+                self.job_seconds_this_month,
+                quotas.limit(MeteredResource::JobTime),
+            )
     }
 
     pub fn quota_violations(&self, quotas: &TenantQuotas) -> Vec<QuotaViolation> {
@@ -300,6 +418,15 @@ impl TenantUsage {
             }
         }
 
+        if let Some(max) = quotas.max_media {
+            if self.media_count >= max {
+                violations.push(QuotaViolation::Media {
+                    current: self.media_count,
+                    max,
+                });
+            }
+        }
+
         if let Some(max) = quotas.max_api_requests_per_day {
             if self.api_requests_today >= max {
                 violations.push(QuotaViolation::ApiRequests {
@@ -309,20 +436,107 @@ impl TenantUsage {
             }
         }
 
+        for resource in [MeteredResource::Bandwidth, MeteredResource::JobTime] {
+            if let Some(max) = quotas.limit(resource) {
+                let current = self.metered(resource);
+                if current >= max {
+                    violations.push(QuotaViolation::of(resource, current, max));
+                }
+            }
+        }
+
         violations
     }
+
+    /// Usage of a metered resource in its unit of metering
+    pub fn metered(&self, resource: MeteredResource) -> u64 {
+        match resource {
+            MeteredResource::Storage => self.storage_bytes,
+            MeteredResource::Media => u64::from(self.media_count),
+            MeteredResource::Bandwidth => self.bandwidth_bytes_this_month,
+            MeteredResource::JobTime => self.job_seconds_this_month,
+        }
+    }
+
+    /// Fail with [`Error::QuotaExceeded`] when `amount` more of `resource`
+    /// would take usage over its quota
+    pub fn check(
+        &self,
+        quotas: &TenantQuotas,
+        resource: MeteredResource,
+        amount: u64,
+    ) -> Result<()> {
+        let Some(max) = quotas.limit(resource) else {
+            return Ok(());
+        };
+        let current = self.metered(resource);
+        if current.saturating_add(amount) > max {
+            let violation = QuotaViolation::of(resource, current, max);
+            return Err(Error::quota_exceeded(
+                resource.as_str(),
+                violation.to_string(),
+            ));
+        }
+        Ok(())
+    }
 }
 
 /// Quota violation types
 #[derive(Debug, Clone)]
 pub enum QuotaViolation {
-    Users { current: u32, max: u32 },
-    Storage { current: u64, max: u64 },
-    Posts { current: u32, max: u32 },
-    Pages { current: u32, max: u32 },
-    Media { current: u32, max: u32 },
-    ApiRequests { current: u32, max: u32 },
-    Plugins { current: u32, max: u32 },
+    Users {
+        current: u32,
+        max: u32,
+    },
+    Storage {
+        current: u64,
+        max: u64,
+    },
+    Posts {
+        current: u32,
+        max: u32,
+    },
+    Pages {
+        current: u32,
+        max: u32,
+    },
+    Media {
+        current: u32,
+        max: u32,
+    },
+    ApiRequests {
+        current: u32,
+        max: u32,
+    },
+    Plugins {
+        current: u32,
+        max: u32,
+    },
+    Bandwidth {
+        current: u64,
+        max: u64,
+    },
+    /// Job time in seconds
+    JobTime {
+        current: u64,
+        max: u64,
+    },
+}
+
+impl QuotaViolation {
+    /// Violation of a metered resource, amounts in its unit of metering
+    pub fn of(resource: MeteredResource, current: u64, max: u64) -> Self {
+        let count = |n: u64| u32::try_from(n).unwrap_or(u32::MAX);
+        match resource {
+            MeteredResource::Storage => Self::Storage { current, max },
+            MeteredResource::Media => Self::Media {
+                current: count(current),
+                max: count(max),
+            },
+            MeteredResource::Bandwidth => Self::Bandwidth { current, max },
+            MeteredResource::JobTime => Self::JobTime { current, max },
+        }
+    }
 }
 
 impl std::fmt::Display for QuotaViolation {
@@ -353,6 +567,140 @@ impl std::fmt::Display for QuotaViolation {
             Self::Plugins { current, max } => {
                 write!(f, "Plugin quota exceeded: {} of {} plugins", current, max)
             }
+            Self::Bandwidth { current, max } => {
+                write!(
+                    f,
+                    "Bandwidth quota exceeded: {} of {} bytes this month",
+                    current, max
+                )
+            }
+            Self::JobTime { current, max } => {
+                write!(
+                    f,
+                    "Job time quota exceeded: {} of {} minutes this month",
+                    current.div_ceil(60),
+                    max / 60
+                )
+            }
+        }
+    }
+}
+
+/// Resources metered per site, so hosting providers can bill by usage.
+/// Storage and media are totals; bandwidth and job time count per
+/// calendar month.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MeteredResource {
+    /// Bytes of files kept in storage
+    Storage,
+    /// Files kept in storage
+    Media,
+    /// Bytes of responses served
+    Bandwidth,
+    /// Seconds spent running background jobs
+    JobTime,
+}
+
+impl MeteredResource {
+    pub const ALL: [MeteredResource; 4] = [
+        MeteredResource::Storage,
+        MeteredResource::Media,
+        MeteredResource::Bandwidth,
+        MeteredResource::JobTime,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Storage => "storage",
+            Self::Media => "media",
+            Self::Bandwidth => "bandwidth",
+            Self::JobTime => "job_time",
+        }
+    }
+
+    /// Whether usage starts over every calendar month
+    pub fn is_monthly(&self) -> bool {
+        matches!(self, Self::Bandwidth | Self::JobTime)
+    }
+}
+
+/// Checks and records what sites of a network use. Storage, the job
+/// worker and the server call it; the main site is not metered.
+#[async_trait]
+pub trait UsageMeter: Send + Sync {
+    /// Fail with [`Error::QuotaExceeded`] when `amount` more of
+    /// `resource` would take the site over its quota
+    async fn check(&self, site: Uuid, resource: MeteredResource, amount: u64) -> Result<()>;
+
+    /// Add to the site's usage; negative amounts give storage back
+    async fn record(&self, site: Uuid, resource: MeteredResource, amount: i64);
+}
+
+/// Kind of installable extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExtensionKind {
+    Theme,
+    Plugin,
+}
+
+impl std::fmt::Display for ExtensionKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Theme => write!(f, "Theme"),
+            Self::Plugin => write!(f, "Plugin"),
+        }
+    }
+}
+
+/// Themes and plugins a tenant or site may install and activate.
+/// `None` allows every extension of that kind; an empty set allows none.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtensionAllowlist {
+    #[serde(default)]
+    pub themes: Option<BTreeSet<String>>,
+    #[serde(default)]
+    pub plugins: Option<BTreeSet<String>>,
+}
+
+impl ExtensionAllowlist {
+    /// Allowed IDs of one kind, `None` when unrestricted
+    pub fn ids(&self, kind: ExtensionKind) -> Option<&BTreeSet<String>> {
+        match kind {
+            ExtensionKind::Theme => self.themes.as_ref(),
+            ExtensionKind::Plugin => self.plugins.as_ref(),
+        }
+    }
+
+    pub fn is_unrestricted(&self) -> bool {
+        self.themes.is_none() && self.plugins.is_none()
+    }
+
+    pub fn allows(&self, kind: ExtensionKind, id: &str) -> bool {
+        self.ids(kind).is_none_or(|ids| ids.contains(id))
+    }
+
+    /// Extensions allowed by both lists
+    pub fn intersect(&self, other: &Self) -> Self {
+        let both = |a: &Option<BTreeSet<String>>, b: &Option<BTreeSet<String>>| match (a, b) {
+            (Some(a), Some(b)) => Some(a.intersection(b).cloned().collect()),
+            (Some(ids), None) | (None, Some(ids)) => Some(ids.clone()),
+            (None, None) => None,
+        };
+        Self {
+            themes: both(&self.themes, &other.themes),
+            plugins: both(&self.plugins, &other.plugins),
+        }
+    }
+
+    /// Fail with [`Error::ExtensionNotAllowed`] naming `scope` (e.g.
+    /// "tenant 'acme'") unless the extension is allowed
+    pub fn check(&self, kind: ExtensionKind, id: &str, scope: &str) -> Result<()> {
+        if self.allows(kind, id) {
+            Ok(())
+        } else {
+            Err(Error::extension_not_allowed(kind.to_string(), id, scope))
         }
     }
 }
@@ -396,6 +744,56 @@ mod tests {
         assert!(!tenant.can_access());
     }
 
+    #[test]
+    fn test_extension_allowlist() {
+        let ids = |ids: &[&str]| Some(ids.iter().map(|id| id.to_string()).collect());
+        let network = ExtensionAllowlist {
+            themes: ids(&["developer", "starter"]),
+            plugins: None,
+        };
+        let tenant = ExtensionAllowlist {
+            themes: ids(&["starter", "magazine"]),
+            plugins: ids(&["seo"]),
+        };
+
+        assert!(ExtensionAllowlist::default().is_unrestricted());
+        assert!(network.allows(ExtensionKind::Plugin, "anything"));
+
+        let effective = network.intersect(&tenant);
+        assert!(effective.allows(ExtensionKind::Theme, "starter"));
+        assert!(!effective.allows(ExtensionKind::Theme, "magazine"));
+        assert!(!effective.allows(ExtensionKind::Plugin, "forms"));
+
+        let err = effective
+            .check(ExtensionKind::Plugin, "forms", "tenant 'acme'")
+            .unwrap_err();
+        assert_eq!(err.status_code(), 403);
+        assert_eq!(
+            err.to_string(),
+            "Plugin 'forms' is not allowed for tenant 'acme'"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_site_scope() {
+        let site = Uuid::new_v4();
+        assert_eq!(current_site(), None);
+        assert_eq!(
+            with_site(Some(site), async { current_site() }).await,
+            Some(site)
+        );
+        let nested = with_site(Some(site), with_site(None, async { current_site() })).await;
+        assert_eq!(nested, None);
+
+        let spawned = with_site(Some(site), async { spawn(async { current_site() }) }).await;
+        assert_eq!(spawned.await.unwrap(), Some(site));
+        assert_eq!(
+            with_site(Some(site), async { site_filter("site_id") }).await,
+            format!("site_id = '{}'", site)
+        );
+        assert_eq!(site_filter("p.site_id"), "p.site_id IS NULL");
+    }
+
     #[test]
     fn test_quota_check() {
         let quotas = TenantQuotas::free_tier();
@@ -421,8 +819,43 @@ mod tests {
             media_count: 1000000,
             api_requests_today: 1000000,
             plugin_count: 1000000,
+            bandwidth_bytes_this_month: u64::MAX,
+            job_seconds_this_month: u64::MAX,
         };
 
         assert!(usage.is_within_quota(&quotas));
     }
+
+    #[test]
+    fn test_metered_quota_check() {
+        let quotas = TenantQuotas {
+            max_media: Some(2),
+            max_job_minutes_per_month: Some(1),
+            ..TenantQuotas::unlimited()
+        };
+        let usage = TenantUsage {
+            media_count: 1,
+            job_seconds_this_month: 61,
+            ..Default::default()
+        };
+
+        assert!(usage.check(&quotas, MeteredResource::Media, 1).is_ok());
+        let err = usage.check(&quotas, MeteredResource::Media, 2).unwrap_err();
+        assert_eq!(err.error_code(), "QUOTA_EXCEEDED");
+        assert_eq!(err.to_string(), "Media quota exceeded: 1 of 2 files");
+        assert!(usage
+            .check(&quotas, MeteredResource::Storage, u64::MAX)
+            .is_ok());
+
+        let violations = usage.quota_violations(&quotas);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(
+            violations[0].to_string(),
+            "Job time quota exceeded: 2 of 1 minutes this month"
+        );
+        assert_eq!(
+            TenantQuotas::for_plan(&TenantPlan::parse("starter")),
+            TenantQuotas::starter_tier()
+        );
+    }
 }
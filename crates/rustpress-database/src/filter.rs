@@ -0,0 +1,680 @@
+//! Filter expressions for list endpoints.
+//!
+//! List endpoints accept a `filter` query parameter such as
+//!
+//! ```text
+//! status:published AND category:rust AND published_at>2024-01-01
+//! ```
+//!
+//! Each clause is `field`, an operator and a value; clauses are joined
+//! with `AND`. Every resource declares a [`FilterSchema`] that whitelists
+//! the fields it can be filtered on, the column or relation behind each
+//! field, the value type and the operators allowed. Anything outside the
+//! schema is rejected, and values are validated against their type before
+//! being rendered into SQL, so user input never reaches a query verbatim.
+//!
+//! | Operator | Meaning                                  |
+//! |----------|------------------------------------------|
+//! | `:`      | equals; `a\|b` matches any, `null` is null |
+//! | `!:`     | not equals; `a\|b` matches none, `null` is not null |
+//! | `>` `>=` `<` `<=` | comparisons for dates and numbers |
+//! | `~`      | contains (case-insensitive)              |
+//! | `^`      | starts with (case-insensitive)           |
+//!
+//! Values containing spaces are written in double quotes:
+//! `title~"hello world"`.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use rustpress_core::error::{Error, Result};
+use rustpress_core::service::{Filter, FilterOperator, FilterValue};
+use uuid::Uuid;
+
+use crate::repository::QueryHelper;
+use FilterOperator::*;
+
+/// Longest accepted filter expression
+pub const MAX_FILTER_LENGTH: usize = 1000;
+
+/// Most clauses a filter may contain
+pub const MAX_FILTER_CLAUSES: usize = 20;
+
+/// Most alternatives in a single `a|b|c` value
+pub const MAX_FILTER_VALUES: usize = 50;
+
+const EQUALITY: &[FilterOperator] = &[Equals, NotEquals, In, NotIn];
+const NULLABLE: &[FilterOperator] = &[Equals, NotEquals, In, NotIn, IsNull, IsNotNull];
+const TEXT: &[FilterOperator] = &[Equals, NotEquals, In, NotIn, Contains, StartsWith];
+const ORDERED: &[FilterOperator] = &[
+    Equals,
+    NotEquals,
+    GreaterThan,
+    GreaterThanOrEqual,
+    LessThan,
+    LessThanOrEqual,
+    IsNull,
+    IsNotNull,
+];
+
+/// Type of a filterable field's values
+#[derive(Debug, Clone, Copy)]
+pub enum FieldKind {
+    Text,
+    /// One of a fixed set of values
+    Enum(&'static [&'static str]),
+    Uuid,
+    /// `YYYY-MM-DD` or an RFC 3339 timestamp
+    Timestamp,
+    Integer,
+    Boolean,
+}
+
+/// What a filter field is matched against
+#[derive(Debug, Clone, Copy)]
+pub enum FieldTarget {
+    /// A column of the listed table
+    Column(&'static str),
+    /// Slugs of the post's terms in a taxonomy
+    Term(&'static str),
+}
+
+/// A field a resource can be filtered on
+#[derive(Debug, Clone, Copy)]
+pub struct FilterField {
+    pub name: &'static str,
+    pub target: FieldTarget,
+    pub kind: FieldKind,
+    pub operators: &'static [FilterOperator],
+}
+
+impl FilterField {
+    pub const fn column(
+        name: &'static str,
+        column: &'static str,
+        kind: FieldKind,
+        operators: &'static [FilterOperator],
+    ) -> Self {
+        Self {
+            name,
+            target: FieldTarget::Column(column),
+            kind,
+            operators,
+        }
+    }
+
+    pub const fn term(name: &'static str, taxonomy: &'static str) -> Self {
+        Self {
+            name,
+            target: FieldTarget::Term(taxonomy),
+            kind: FieldKind::Text,
+            operators: EQUALITY,
+        }
+    }
+}
+
+/// Filterable fields of one resource
+#[derive(Debug, Clone, Copy)]
+pub struct FilterSchema {
+    pub resource: &'static str,
+    /// Table the conditions are evaluated against
+    pub table: &'static str,
+    pub fields: &'static [FilterField],
+}
+
+/// Posts and pages
+pub const POST_FILTERS: FilterSchema = FilterSchema {
+    resource: "posts",
+    table: "posts",
+    fields: &[
+        FilterField::column(
+            "status",
+            "status",
+            FieldKind::Enum(&[
+                "draft",
+                "pending",
+                "published",
+                "private",
+                "trash",
+                "scheduled",
+            ]),
+            EQUALITY,
+        ),
+        FilterField::column("post_type", "post_type", FieldKind::Text, EQUALITY),
+        FilterField::column("author", "author_id", FieldKind::Uuid, EQUALITY),
+        FilterField::column("parent", "parent_id", FieldKind::Uuid, NULLABLE),
+        FilterField::column("title", "title", FieldKind::Text, TEXT),
+        FilterField::column("slug", "slug", FieldKind::Text, TEXT),
+        FilterField::column(
+            "published_at",
+            "published_at",
+            FieldKind::Timestamp,
+            ORDERED,
+        ),
+        FilterField::column("created_at", "created_at", FieldKind::Timestamp, ORDERED),
+        FilterField::column("updated_at", "updated_at", FieldKind::Timestamp, ORDERED),
+        FilterField::column(
+            "comment_count",
+            "comment_count",
+            FieldKind::Integer,
+            ORDERED,
+        ),
+        FilterField::term("category", "category"),
+        FilterField::term("tag", "post_tag"),
+    ],
+};
+
+/// Media library items
+pub const MEDIA_FILTERS: FilterSchema = FilterSchema {
+    resource: "media",
+    table: "media",
+    fields: &[
+        FilterField::column("mime_type", "mime_type", FieldKind::Text, TEXT),
+        FilterField::column("uploader", "uploader_id", FieldKind::Uuid, NULLABLE),
+        FilterField::column("filename", "original_filename", FieldKind::Text, TEXT),
+        FilterField::column("file_size", "file_size", FieldKind::Integer, ORDERED),
+        FilterField::column("width", "width", FieldKind::Integer, ORDERED),
+        FilterField::column("height", "height", FieldKind::Integer, ORDERED),
+        FilterField::column("created_at", "created_at", FieldKind::Timestamp, ORDERED),
+    ],
+};
+
+/// User accounts
+pub const USER_FILTERS: FilterSchema = FilterSchema {
+    resource: "users",
+    table: "users",
+    fields: &[
+        FilterField::column(
+            "status",
+            "status",
+            FieldKind::Enum(&["pending", "active", "suspended", "inactive"]),
+            EQUALITY,
+        ),
+        FilterField::column(
+            "role",
+            "role",
+            FieldKind::Enum(&[
+                "subscriber",
+                "contributor",
+                "author",
+                "editor",
+                "administrator",
+            ]),
+            EQUALITY,
+        ),
+        FilterField::column("username", "username", FieldKind::Text, TEXT),
+        FilterField::column("email", "email", FieldKind::Text, TEXT),
+        FilterField::column("created_at", "created_at", FieldKind::Timestamp, ORDERED),
+        FilterField::column(
+            "last_login_at",
+            "last_login_at",
+            FieldKind::Timestamp,
+            ORDERED,
+        ),
+    ],
+};
+
+/// Comments
+pub const COMMENT_FILTERS: FilterSchema = FilterSchema {
+    resource: "comments",
+    table: "comments",
+    fields: &[
+        FilterField::column(
+            "status",
+            "status",
+            FieldKind::Enum(&["pending", "approved", "spam", "trash"]),
+            EQUALITY,
+        ),
+        FilterField::column("post", "post_id", FieldKind::Uuid, EQUALITY),
+        FilterField::column("parent", "parent_id", FieldKind::Uuid, NULLABLE),
+        FilterField::column("user", "user_id", FieldKind::Uuid, NULLABLE),
+        FilterField::column("author_email", "author_email", FieldKind::Text, TEXT),
+        FilterField::column("created_at", "created_at", FieldKind::Timestamp, ORDERED),
+        FilterField::column("likes_count", "likes_count", FieldKind::Integer, ORDERED),
+    ],
+};
+
+impl FilterSchema {
+    fn field(&self, name: &str) -> Result<&FilterField> {
+        self.fields
+            .iter()
+            .find(|field| field.name == name)
+            .ok_or_else(|| {
+                let known: Vec<&str> = self.fields.iter().map(|field| field.name).collect();
+                Error::invalid_input(
+                    "filter",
+                    format!(
+                        "Unknown {} filter field '{}'; expected one of: {}",
+                        self.resource,
+                        name,
+                        known.join(", ")
+                    ),
+                )
+            })
+    }
+
+    /// Parse a filter expression into validated filters
+    pub fn parse(&self, input: &str) -> Result<Vec<Filter>> {
+        if input.len() > MAX_FILTER_LENGTH {
+            return Err(invalid(format!(
+                "Filter is longer than {} characters",
+                MAX_FILTER_LENGTH
+            )));
+        }
+
+        let mut parser = Parser::new(input);
+        let mut filters = Vec::new();
+        parser.skip_whitespace();
+        while !parser.at_end() {
+            if filters.len() == MAX_FILTER_CLAUSES {
+                return Err(invalid(format!(
+                    "Filter has more than {} clauses",
+                    MAX_FILTER_CLAUSES
+                )));
+            }
+            if !filters.is_empty() {
+                parser.expect_and()?;
+            }
+
+            let (name, op, raw) = parser.clause()?;
+            let field = self.field(name)?;
+            let filter = build_filter(field, op, raw)?;
+            if !field.operators.contains(&filter.operator) {
+                return Err(invalid(format!(
+                    "Operator '{}' is not supported for '{}'",
+                    op, field.name
+                )));
+            }
+            // Validate values against the field type up front
+            self.condition(field, &filter)?;
+            filters.push(filter);
+            parser.skip_whitespace();
+        }
+
+        Ok(filters)
+    }
+
+    /// SQL conditions for parsed filters, to be joined with `AND`
+    pub fn conditions(&self, filters: &[Filter]) -> Result<Vec<String>> {
+        filters
+            .iter()
+            .map(|filter| {
+                let field = self.field(&filter.field)?;
+                if !field.operators.contains(&filter.operator) {
+                    return Err(invalid(format!(
+                        "Operator {:?} is not supported for '{}'",
+                        filter.operator, field.name
+                    )));
+                }
+                self.condition(field, filter)
+            })
+            .collect()
+    }
+
+    /// Parse an optional filter expression straight into SQL conditions
+    pub fn parse_conditions(&self, input: Option<&str>) -> Result<Vec<String>> {
+        match input.map(str::trim).filter(|input| !input.is_empty()) {
+            Some(input) => self.conditions(&self.parse(input)?),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    fn condition(&self, field: &FilterField, filter: &Filter) -> Result<String> {
+        match field.target {
+            FieldTarget::Column(column) => column_condition(column, field.kind, filter),
+            FieldTarget::Term(taxonomy) => {
+                let (negated, slugs) = match (&filter.operator, &filter.value) {
+                    (Equals, value) => (false, vec![literal(FieldKind::Text, value)?]),
+                    (NotEquals, value) => (true, vec![literal(FieldKind::Text, value)?]),
+                    (In, FilterValue::Array(values)) => (false, literals(FieldKind::Text, values)?),
+                    (NotIn, FilterValue::Array(values)) => {
+                        (true, literals(FieldKind::Text, values)?)
+                    }
+                    _ => return Err(invalid(format!("Invalid filter for '{}'", field.name))),
+                };
+                Ok(format!(
+                    "{}EXISTS (SELECT 1 FROM post_terms pt \
+                     JOIN terms t ON t.id = pt.term_id \
+                     JOIN taxonomies tx ON tx.id = t.taxonomy_id \
+                     WHERE pt.post_id = {}.id AND tx.slug = '{}' AND t.slug IN ({}))",
+                    if negated { "NOT " } else { "" },
+                    self.table,
+                    taxonomy,
+                    slugs.join(", ")
+                ))
+            }
+        }
+    }
+}
+
+fn invalid(message: String) -> Error {
+    Error::invalid_input("filter", message)
+}
+
+fn column_condition(column: &str, kind: FieldKind, filter: &Filter) -> Result<String> {
+    let comparison = |sql_op: &str| -> Result<String> {
+        Ok(format!(
+            "{} {} {}",
+            column,
+            sql_op,
+            literal(kind, &filter.value)?
+        ))
+    };
+    let pattern = |prefix: &str| -> Result<String> {
+        match &filter.value {
+            FilterValue::String(value) => Ok(format!(
+                "{} ILIKE '{}{}%'",
+                column,
+                prefix,
+                QueryHelper::escape_like(value).replace('\'', "''")
+            )),
+            _ => Err(invalid(format!("'{}' needs a text value", column))),
+        }
+    };
+    let list = |sql_op: &str| -> Result<String> {
+        match &filter.value {
+            FilterValue::Array(values) => Ok(format!(
+                "{} {} ({})",
+                column,
+                sql_op,
+                literals(kind, values)?.join(", ")
+            )),
+            _ => Err(invalid(format!("'{}' needs a list of values", column))),
+        }
+    };
+
+    match filter.operator {
+        Equals => comparison("="),
+        NotEquals => comparison("<>"),
+        GreaterThan => comparison(">"),
+        GreaterThanOrEqual => comparison(">="),
+        LessThan => comparison("<"),
+        LessThanOrEqual => comparison("<="),
+        Contains => pattern("%"),
+        StartsWith => pattern(""),
+        EndsWith => Err(invalid("Ends-with filters are not supported".to_string())),
+        In => list("IN"),
+        NotIn => list("NOT IN"),
+        IsNull => Ok(format!("{} IS NULL", column)),
+        IsNotNull => Ok(format!("{} IS NOT NULL", column)),
+    }
+}
+
+fn literals(kind: FieldKind, values: &[FilterValue]) -> Result<Vec<String>> {
+    if values.is_empty() || values.len() > MAX_FILTER_VALUES {
+        return Err(invalid(format!(
+            "Lists must have between 1 and {} values",
+            MAX_FILTER_VALUES
+        )));
+    }
+    values.iter().map(|value| literal(kind, value)).collect()
+}
+
+/// Render a value as an SQL literal after checking it against the field type
+fn literal(kind: FieldKind, value: &FilterValue) -> Result<String> {
+    match (kind, value) {
+        (FieldKind::Integer, FilterValue::Integer(n)) => Ok(n.to_string()),
+        (FieldKind::Boolean, FilterValue::Boolean(b)) => Ok(b.to_string()),
+        (FieldKind::Text, FilterValue::String(s)) => Ok(format!("'{}'", s.replace('\'', "''"))),
+        (FieldKind::Enum(allowed), FilterValue::String(s)) if allowed.contains(&s.as_str()) => {
+            Ok(format!("'{}'", s))
+        }
+        (FieldKind::Uuid, FilterValue::String(s)) => Uuid::parse_str(s)
+            .map(|id| format!("'{}'", id))
+            .map_err(|_| invalid(format!("'{}' is not a valid ID", s))),
+        (FieldKind::Timestamp, FilterValue::String(s)) => {
+            parse_timestamp(s).map(|at| format!("'{}'", at.to_rfc3339()))
+        }
+        (FieldKind::Enum(allowed), value) => Err(invalid(format!(
+            "{} is not one of: {}",
+            describe(value),
+            allowed.join(", ")
+        ))),
+        (kind, value) => Err(invalid(format!(
+            "{} is not a valid {:?} value",
+            describe(value),
+            kind
+        ))),
+    }
+}
+
+fn describe(value: &FilterValue) -> String {
+    match value {
+        FilterValue::String(s) => format!("'{}'", s),
+        FilterValue::Integer(n) => n.to_string(),
+        FilterValue::Float(n) => n.to_string(),
+        FilterValue::Boolean(b) => b.to_string(),
+        FilterValue::Array(_) => "A list".to_string(),
+        FilterValue::Null => "null".to_string(),
+    }
+}
+
+fn parse_timestamp(value: &str) -> Result<DateTime<Utc>> {
+    if let Ok(at) = DateTime::parse_from_rfc3339(value) {
+        return Ok(at.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|at| at.and_utc())
+        .ok_or_else(|| {
+            invalid(format!(
+                "'{}' is not a date (YYYY-MM-DD) or RFC 3339 timestamp",
+                value
+            ))
+        })
+}
+
+/// Turn one clause's operator and raw value into a typed filter
+fn build_filter(field: &FilterField, op: &str, raw: RawValue) -> Result<Filter> {
+    let typed = |value: &str| -> Result<FilterValue> {
+        match field.kind {
+            FieldKind::Integer => value
+                .parse::<i64>()
+                .map(FilterValue::Integer)
+                .map_err(|_| invalid(format!("'{}' is not a number", value))),
+            FieldKind::Boolean => match value {
+                "true" => Ok(FilterValue::Boolean(true)),
+                "false" => Ok(FilterValue::Boolean(false)),
+                _ => Err(invalid(format!("'{}' is not true or false", value))),
+            },
+            _ => Ok(FilterValue::String(value.to_string())),
+        }
+    };
+
+    let is_null = !raw.quoted && raw.text == "null";
+    let alternatives: Vec<&str> = if raw.quoted {
+        vec![raw.text.as_str()]
+    } else {
+        raw.text.split('|').collect()
+    };
+
+    let (operator, value) = match op {
+        ":" | "!:" if is_null => (
+            if op == ":" { IsNull } else { IsNotNull },
+            FilterValue::Null,
+        ),
+        ":" | "!:" if alternatives.len() > 1 => (
+            if op == ":" { In } else { NotIn },
+            FilterValue::Array(
+                alternatives
+                    .into_iter()
+                    .map(typed)
+                    .collect::<Result<Vec<_>>>()?,
+            ),
+        ),
+        ":" => (Equals, typed(&raw.text)?),
+        "!:" => (NotEquals, typed(&raw.text)?),
+        ">" => (GreaterThan, typed(&raw.text)?),
+        ">=" => (GreaterThanOrEqual, typed(&raw.text)?),
+        "<" => (LessThan, typed(&raw.text)?),
+        "<=" => (LessThanOrEqual, typed(&raw.text)?),
+        "~" => (Contains, FilterValue::String(raw.text.clone())),
+        "^" => (StartsWith, FilterValue::String(raw.text.clone())),
+        _ => return Err(invalid(format!("Unknown operator '{}'", op))),
+    };
+
+    Ok(Filter {
+        field: field.name.to_string(),
+        operator,
+        value,
+    })
+}
+
+struct RawValue {
+    text: String,
+    quoted: bool,
+}
+
+/// Hand-written scanner for `field op value [AND ...]`
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Self {
+        Self { input, pos: 0 }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos >= self.input.len()
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn expect_and(&mut self) -> Result<()> {
+        let rest = self.rest();
+        let word_len = rest.find(|c: char| c.is_whitespace()).unwrap_or(rest.len());
+        let word = &rest[..word_len];
+        if word.eq_ignore_ascii_case("and") {
+            self.pos += word_len;
+            self.skip_whitespace();
+            if self.at_end() {
+                return Err(invalid("Filter ends with AND".to_string()));
+            }
+            Ok(())
+        } else if word.eq_ignore_ascii_case("or") {
+            Err(invalid(
+                "OR is not supported; use a|b for alternatives".to_string(),
+            ))
+        } else {
+            Err(invalid(format!("Expected AND before '{}'", word)))
+        }
+    }
+
+    fn clause(&mut self) -> Result<(&'a str, &'a str, RawValue)> {
+        let rest = self.rest();
+        let name_len = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        if name_len == 0 {
+            return Err(invalid(format!("Expected a field name at '{}'", rest)));
+        }
+        let name = &rest[..name_len];
+        self.pos += name_len;
+
+        let rest = self.rest();
+        let op = ["!:", ">=", "<=", ":", ">", "<", "~", "^"]
+            .into_iter()
+            .find(|op| rest.starts_with(op))
+            .ok_or_else(|| invalid(format!("Expected an operator after '{}'", name)))?;
+        self.pos += op.len();
+
+        let value = self.value()?;
+        if value.text.is_empty() && !value.quoted {
+            return Err(invalid(format!("Missing value for '{}'", name)));
+        }
+        Ok((name, op, value))
+    }
+
+    fn value(&mut self) -> Result<RawValue> {
+        let rest = self.rest();
+        let Some(quoted) = rest.strip_prefix('"') else {
+            let len = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            self.pos += len;
+            return Ok(RawValue {
+                text: rest[..len].to_string(),
+                quoted: false,
+            });
+        };
+
+        let mut text = String::new();
+        let mut chars = quoted.char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.pos += i + 2;
+                    return Ok(RawValue { text, quoted: true });
+                }
+                '\\' => match chars.next() {
+                    Some((_, escaped)) => text.push(escaped),
+                    None => break,
+                },
+                c => text.push(c),
+            }
+        }
+        Err(invalid("Unterminated quoted value".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_compile() {
+        let conditions = POST_FILTERS
+            .parse_conditions(Some(
+                "status:published AND category:rust and published_at>2024-01-01 AND title~\"it's 100%\"",
+            ))
+            .unwrap();
+
+        assert_eq!(conditions[0], "status = 'published'");
+        assert!(conditions[1].starts_with("EXISTS (SELECT 1 FROM post_terms"));
+        assert!(conditions[1].contains("tx.slug = 'category' AND t.slug IN ('rust')"));
+        assert_eq!(conditions[2], "published_at > '2024-01-01T00:00:00+00:00'");
+        assert_eq!(conditions[3], "title ILIKE '%it''s 100\\%%'");
+
+        let conditions = COMMENT_FILTERS
+            .parse_conditions(Some("status!:spam|trash AND parent:null"))
+            .unwrap();
+        assert_eq!(conditions[0], "status NOT IN ('spam', 'trash')");
+        assert_eq!(conditions[1], "parent_id IS NULL");
+
+        assert!(POST_FILTERS
+            .parse_conditions(Some("  "))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_rejects_unsafe_or_unknown_input() {
+        let rejected = [
+            "password_hash:x",
+            "status:published'; DROP TABLE posts; --",
+            "author:not-a-uuid",
+            "published_at>yesterday",
+            "status~pub",
+            "status:draft OR status:published",
+            "status:draft status:published",
+            "title~\"unterminated",
+            "status:",
+        ];
+        for input in rejected {
+            assert!(POST_FILTERS.parse(input).is_err(), "accepted {}", input);
+        }
+
+        assert!(MEDIA_FILTERS.parse("file_size>=abc").is_err());
+        assert!(USER_FILTERS.parse("role:superuser").is_err());
+        assert!(USER_FILTERS.parse("role:editor AND").is_err());
+
+        let many = vec!["status:draft"; MAX_FILTER_CLAUSES + 1].join(" AND ");
+        assert!(POST_FILTERS.parse(&many).is_err());
+    }
+}
@@ -30,14 +30,18 @@
 //! - Point 54: Redirects table for URL management
 //! - Point 55: Multi-site tables structure for network installations
 
+pub mod filter;
 pub mod migration;
 pub mod models;
 pub mod pool;
+pub mod providers;
 pub mod repository;
 pub mod schema;
 pub mod transaction;
 
+pub use filter::FilterSchema;
 pub use migration::Migrator;
 pub use pool::{DatabasePool, PoolConfig};
+pub use providers::{DatabaseProvider, DbRow, DbValue, ProviderPool};
 pub use schema::*;
 pub use transaction::Transaction;
@@ -37,9 +37,14 @@ pub struct MediaRow {
     pub height: Option<i32>,
     pub duration: Option<i32>,
     pub metadata: serde_json::Value,
+    #[sqlx(default)]
+    pub folder_id: Option<Uuid>,
+    #[sqlx(default)]
+    pub tags: Vec<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub deleted_at: Option<DateTime<Utc>>,
+    pub version: i64,
 }
 
 /// Page model - stored in posts table with post_type='page'
@@ -1,6 +1,7 @@
 //! Database connection pool management.
 
 use rustpress_core::error::{Error, Result};
+use rustpress_core::fault::{FaultInjector, FaultTarget};
 use sqlx::postgres::PgPoolOptions;
 use sqlx::{PgPool, Postgres};
 use std::sync::Arc;
@@ -15,6 +16,8 @@ pub struct PoolConfig {
     pub connect_timeout: Duration,
     pub idle_timeout: Duration,
     pub max_lifetime: Duration,
+    /// Faults injected when connections are handed out
+    pub faults: Option<FaultInjector>,
 }
 
 impl Default for PoolConfig {
@@ -26,6 +29,7 @@ impl Default for PoolConfig {
             connect_timeout: Duration::from_secs(10),
             idle_timeout: Duration::from_secs(600),
             max_lifetime: Duration::from_secs(1800),
+            faults: None,
         }
     }
 }
@@ -44,10 +48,49 @@ impl From<rustpress_core::config::DatabaseConfig> for PoolConfig {
             connect_timeout,
             idle_timeout,
             max_lifetime,
+            faults: None,
         }
     }
 }
 
+/// Pool options with the fault injection hooks, if any
+///
+/// Faults apply when a connection is acquired, which every query does. An
+/// injected error holds the acquire until its timeout, so callers see the
+/// same `PoolTimedOut` an unreachable primary produces.
+fn pool_options(config: &PoolConfig) -> PgPoolOptions {
+    let options = PgPoolOptions::new();
+    let Some(faults) = config.faults.clone() else {
+        return options;
+    };
+
+    let acquire_timeout = config.connect_timeout;
+    let on_connect = faults.clone();
+    options
+        .before_acquire(move |_, _| {
+            let faults = faults.clone();
+            Box::pin(async move {
+                inject_database_fault(&faults, acquire_timeout).await?;
+                Ok(true)
+            })
+        })
+        .after_connect(move |_, _| {
+            let faults = on_connect.clone();
+            Box::pin(async move { inject_database_fault(&faults, acquire_timeout).await })
+        })
+}
+
+async fn inject_database_fault(
+    faults: &FaultInjector,
+    acquire_timeout: Duration,
+) -> std::result::Result<(), sqlx::Error> {
+    if let Err(e) = faults.inject(FaultTarget::Database, None).await {
+        tokio::time::sleep(acquire_timeout).await;
+        return Err(sqlx::Error::Protocol(e.to_string()));
+    }
+    Ok(())
+}
+
 /// Database pool wrapper
 #[derive(Clone)]
 pub struct DatabasePool {
@@ -58,7 +101,7 @@ pub struct DatabasePool {
 impl DatabasePool {
     /// Create a new database pool
     pub async fn new(config: PoolConfig) -> Result<Self> {
-        let pool = PgPoolOptions::new()
+        let pool = pool_options(&config)
             .min_connections(config.min_connections)
             .max_connections(config.max_connections)
             .acquire_timeout(config.connect_timeout)
@@ -80,6 +123,23 @@ impl DatabasePool {
         })
     }
 
+    /// Create a pool that only connects once a connection is first needed
+    pub fn connect_lazy(config: PoolConfig) -> Result<Self> {
+        let pool = pool_options(&config)
+            .min_connections(0)
+            .max_connections(config.max_connections)
+            .acquire_timeout(config.connect_timeout)
+            .idle_timeout(config.idle_timeout)
+            .max_lifetime(config.max_lifetime)
+            .connect_lazy(&config.url)
+            .map_err(|e| Error::database_with_source("Invalid database URL", e))?;
+
+        Ok(Self {
+            pool,
+            config: Arc::new(config),
+        })
+    }
+
     /// Get a reference to the underlying pool
     pub fn inner(&self) -> &PgPool {
         &self.pool
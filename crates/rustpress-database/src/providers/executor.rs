@@ -0,0 +1,913 @@
+//! Backend-neutral query execution
+//!
+//! [`ProviderPool`] is the connection pool a [`DatabaseProvider`] hands
+//! out, whichever backend it connected to. Queries are written once in
+//! PostgreSQL placeholder style (`$1`, `$2`, ...) with [`DbValue`]
+//! parameters; the pool rewrites placeholders for MySQL (`?`) and SQLite
+//! (`?1`) and decodes result rows into [`DbRow`]s, so repositories can
+//! run against any provider without matching on the backend.
+//!
+//! ```ignore
+//! let pool = provider.pool().expect("connected");
+//! let row = pool
+//!     .fetch_one("SELECT id, title FROM posts WHERE slug = $1", &["hello".into()])
+//!     .await?;
+//! let id: Uuid = row.get("id")?;
+//! ```
+//!
+//! Null parameters are inlined as `NULL` rather than bound, so they take
+//! whatever type the surrounding expression expects on every backend.
+//!
+//! [`DatabaseProvider`]: super::DatabaseProvider
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde_json::Value;
+use uuid::Uuid;
+
+use super::{DatabaseError, Result};
+
+/// Backend a pool is connected to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DatabaseKind {
+    Postgres,
+    MySql,
+    Sqlite,
+}
+
+impl DatabaseKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Postgres => "postgres",
+            Self::MySql => "mysql",
+            Self::Sqlite => "sqlite",
+        }
+    }
+}
+
+/// A query parameter or column value
+#[derive(Debug, Clone, PartialEq)]
+pub enum DbValue {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Text(String),
+    Bytes(Vec<u8>),
+    Uuid(Uuid),
+    Timestamp(DateTime<Utc>),
+    Json(Value),
+}
+
+impl From<bool> for DbValue {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl From<i32> for DbValue {
+    fn from(value: i32) -> Self {
+        Self::Int(value.into())
+    }
+}
+
+impl From<i64> for DbValue {
+    fn from(value: i64) -> Self {
+        Self::Int(value)
+    }
+}
+
+impl From<f64> for DbValue {
+    fn from(value: f64) -> Self {
+        Self::Float(value)
+    }
+}
+
+impl From<String> for DbValue {
+    fn from(value: String) -> Self {
+        Self::Text(value)
+    }
+}
+
+impl From<&str> for DbValue {
+    fn from(value: &str) -> Self {
+        Self::Text(value.to_string())
+    }
+}
+
+impl From<Vec<u8>> for DbValue {
+    fn from(value: Vec<u8>) -> Self {
+        Self::Bytes(value)
+    }
+}
+
+impl From<Uuid> for DbValue {
+    fn from(value: Uuid) -> Self {
+        Self::Uuid(value)
+    }
+}
+
+impl From<DateTime<Utc>> for DbValue {
+    fn from(value: DateTime<Utc>) -> Self {
+        Self::Timestamp(value)
+    }
+}
+
+impl From<Value> for DbValue {
+    fn from(value: Value) -> Self {
+        Self::Json(value)
+    }
+}
+
+impl<T: Into<DbValue>> From<Option<T>> for DbValue {
+    fn from(value: Option<T>) -> Self {
+        value.map(Into::into).unwrap_or(Self::Null)
+    }
+}
+
+/// Conversion from a column value
+///
+/// Conversions accept the representations each backend actually stores:
+/// UUIDs and timestamps arrive as text from MySQL and SQLite, booleans as
+/// integers from SQLite.
+pub trait FromDbValue: Sized {
+    fn from_db_value(value: &DbValue) -> Option<Self>;
+}
+
+impl FromDbValue for DbValue {
+    fn from_db_value(value: &DbValue) -> Option<Self> {
+        Some(value.clone())
+    }
+}
+
+impl FromDbValue for bool {
+    fn from_db_value(value: &DbValue) -> Option<Self> {
+        match value {
+            DbValue::Bool(b) => Some(*b),
+            DbValue::Int(0) => Some(false),
+            DbValue::Int(1) => Some(true),
+            _ => None,
+        }
+    }
+}
+
+impl FromDbValue for i64 {
+    fn from_db_value(value: &DbValue) -> Option<Self> {
+        match value {
+            DbValue::Int(i) => Some(*i),
+            DbValue::Bool(b) => Some(i64::from(*b)),
+            _ => None,
+        }
+    }
+}
+
+impl FromDbValue for i32 {
+    fn from_db_value(value: &DbValue) -> Option<Self> {
+        i64::from_db_value(value).and_then(|i| i32::try_from(i).ok())
+    }
+}
+
+impl FromDbValue for f64 {
+    fn from_db_value(value: &DbValue) -> Option<Self> {
+        match value {
+            DbValue::Float(f) => Some(*f),
+            DbValue::Int(i) => Some(*i as f64),
+            _ => None,
+        }
+    }
+}
+
+impl FromDbValue for String {
+    fn from_db_value(value: &DbValue) -> Option<Self> {
+        match value {
+            DbValue::Text(s) => Some(s.clone()),
+            DbValue::Uuid(id) => Some(id.to_string()),
+            _ => None,
+        }
+    }
+}
+
+impl FromDbValue for Vec<u8> {
+    fn from_db_value(value: &DbValue) -> Option<Self> {
+        match value {
+            DbValue::Bytes(bytes) => Some(bytes.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl FromDbValue for Uuid {
+    fn from_db_value(value: &DbValue) -> Option<Self> {
+        match value {
+            DbValue::Uuid(id) => Some(*id),
+            DbValue::Text(s) => Uuid::parse_str(s).ok(),
+            DbValue::Bytes(bytes) => Uuid::from_slice(bytes).ok(),
+            _ => None,
+        }
+    }
+}
+
+impl FromDbValue for DateTime<Utc> {
+    fn from_db_value(value: &DbValue) -> Option<Self> {
+        match value {
+            DbValue::Timestamp(at) => Some(*at),
+            DbValue::Text(s) => DateTime::parse_from_rfc3339(s)
+                .map(|at| at.with_timezone(&Utc))
+                .ok()
+                .or_else(|| {
+                    NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S%.f")
+                        .ok()
+                        .map(|at| at.and_utc())
+                }),
+            _ => None,
+        }
+    }
+}
+
+impl FromDbValue for Value {
+    fn from_db_value(value: &DbValue) -> Option<Self> {
+        match value {
+            DbValue::Json(json) => Some(json.clone()),
+            DbValue::Text(s) => serde_json::from_str(s).ok(),
+            _ => None,
+        }
+    }
+}
+
+impl<T: FromDbValue> FromDbValue for Option<T> {
+    fn from_db_value(value: &DbValue) -> Option<Self> {
+        match value {
+            DbValue::Null => Some(None),
+            value => T::from_db_value(value).map(Some),
+        }
+    }
+}
+
+/// A result row, decoded from whichever backend produced it
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DbRow {
+    columns: Vec<(String, DbValue)>,
+}
+
+impl DbRow {
+    /// Raw value of a column
+    pub fn value(&self, column: &str) -> Option<&DbValue> {
+        self.columns
+            .iter()
+            .find(|(name, _)| name == column)
+            .map(|(_, value)| value)
+    }
+
+    /// Typed value of a column
+    pub fn get<T: FromDbValue>(&self, column: &str) -> Result<T> {
+        let value = self
+            .value(column)
+            .ok_or_else(|| DatabaseError::Query(format!("No column '{}' in row", column)))?;
+        T::from_db_value(value).ok_or_else(|| {
+            DatabaseError::Query(format!(
+                "Column '{}' holds {:?}, which cannot be read as {}",
+                column,
+                value,
+                std::any::type_name::<T>()
+            ))
+        })
+    }
+
+    /// Column names in select order
+    pub fn columns(&self) -> impl Iterator<Item = &str> {
+        self.columns.iter().map(|(name, _)| name.as_str())
+    }
+
+    fn push(&mut self, name: &str, value: DbValue) {
+        self.columns.push((name.to_string(), value));
+    }
+}
+
+/// Rewrite `$n` placeholders for a backend, returning the SQL and the
+/// parameters in bind order. Null parameters become `NULL` literals.
+fn prepare(kind: DatabaseKind, sql: &str, params: &[DbValue]) -> Result<(String, Vec<DbValue>)> {
+    let mut out = String::with_capacity(sql.len());
+    let mut bound: Vec<DbValue> = Vec::new();
+    // Bind position of each distinct parameter, for numbered placeholders
+    let mut positions: Vec<Option<usize>> = vec![None; params.len()];
+
+    let mut chars = sql.char_indices().peekable();
+    let mut quote: Option<char> = None;
+    while let Some((_, c)) = chars.next() {
+        if let Some(q) = quote {
+            out.push(c);
+            if c == q {
+                quote = None;
+            }
+            continue;
+        }
+
+        match c {
+            '\'' | '"' | '`' => {
+                quote = Some(c);
+                out.push(c);
+            }
+            '$' if chars.peek().is_some_and(|(_, next)| next.is_ascii_digit()) => {
+                let mut digits = String::new();
+                while let Some((_, d)) = chars.peek().filter(|(_, d)| d.is_ascii_digit()) {
+                    digits.push(*d);
+                    chars.next();
+                }
+                let index: usize = digits
+                    .parse()
+                    .ok()
+                    .filter(|n| (1..=params.len()).contains(n))
+                    .ok_or_else(|| {
+                        DatabaseError::Query(format!(
+                            "Placeholder ${} has no parameter ({} given)",
+                            digits,
+                            params.len()
+                        ))
+                    })?;
+                let value = &params[index - 1];
+
+                if *value == DbValue::Null {
+                    out.push_str("NULL");
+                    continue;
+                }
+                match kind {
+                    DatabaseKind::MySql => {
+                        bound.push(value.clone());
+                        out.push('?');
+                    }
+                    DatabaseKind::Postgres | DatabaseKind::Sqlite => {
+                        let position = *positions[index - 1].get_or_insert_with(|| {
+                            bound.push(value.clone());
+                            bound.len()
+                        });
+                        out.push(if kind == DatabaseKind::Postgres {
+                            '$'
+                        } else {
+                            '?'
+                        });
+                        out.push_str(&position.to_string());
+                    }
+                }
+            }
+            _ => out.push(c),
+        }
+    }
+
+    Ok((out, bound))
+}
+
+fn query_error(e: sqlx::Error) -> DatabaseError {
+    DatabaseError::Query(e.to_string())
+}
+
+/// Connection pool of whichever backend a provider connected to
+///
+/// Cloning is cheap; clones share the underlying connections.
+#[derive(Debug, Clone)]
+pub enum ProviderPool {
+    Postgres(sqlx::PgPool),
+    #[cfg(feature = "mysql")]
+    MySql(sqlx::MySqlPool),
+    #[cfg(feature = "sqlite")]
+    Sqlite(sqlx::SqlitePool),
+}
+
+impl ProviderPool {
+    pub fn kind(&self) -> DatabaseKind {
+        match self {
+            Self::Postgres(_) => DatabaseKind::Postgres,
+            #[cfg(feature = "mysql")]
+            Self::MySql(_) => DatabaseKind::MySql,
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(_) => DatabaseKind::Sqlite,
+        }
+    }
+
+    /// The PostgreSQL pool, for code that still needs Postgres-only SQL
+    pub fn as_postgres(&self) -> Option<&sqlx::PgPool> {
+        match self {
+            Self::Postgres(pool) => Some(pool),
+            #[allow(unreachable_patterns)]
+            _ => None,
+        }
+    }
+
+    /// Run a statement, returning the number of affected rows
+    pub async fn execute(&self, sql: &str, params: &[DbValue]) -> Result<u64> {
+        let (sql, params) = prepare(self.kind(), sql, params)?;
+        match self {
+            Self::Postgres(pool) => postgres::bind(sqlx::query(&sql), params)
+                .execute(pool)
+                .await
+                .map(|done| done.rows_affected())
+                .map_err(query_error),
+            #[cfg(feature = "mysql")]
+            Self::MySql(pool) => mysql::bind(sqlx::query(&sql), params)
+                .execute(pool)
+                .await
+                .map(|done| done.rows_affected())
+                .map_err(query_error),
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(pool) => sqlite::bind(sqlx::query(&sql), params)
+                .execute(pool)
+                .await
+                .map(|done| done.rows_affected())
+                .map_err(query_error),
+        }
+    }
+
+    /// Run a query and decode every row
+    pub async fn fetch_all(&self, sql: &str, params: &[DbValue]) -> Result<Vec<DbRow>> {
+        let (sql, params) = prepare(self.kind(), sql, params)?;
+        match self {
+            Self::Postgres(pool) => postgres::bind(sqlx::query(&sql), params)
+                .fetch_all(pool)
+                .await
+                .map_err(query_error)?
+                .iter()
+                .map(postgres::decode)
+                .collect(),
+            #[cfg(feature = "mysql")]
+            Self::MySql(pool) => mysql::bind(sqlx::query(&sql), params)
+                .fetch_all(pool)
+                .await
+                .map_err(query_error)?
+                .iter()
+                .map(mysql::decode)
+                .collect(),
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(pool) => sqlite::bind(sqlx::query(&sql), params)
+                .fetch_all(pool)
+                .await
+                .map_err(query_error)?
+                .iter()
+                .map(sqlite::decode)
+                .collect(),
+        }
+    }
+
+    /// Run a query and decode the first row, if any
+    pub async fn fetch_optional(&self, sql: &str, params: &[DbValue]) -> Result<Option<DbRow>> {
+        let (sql, params) = prepare(self.kind(), sql, params)?;
+        match self {
+            Self::Postgres(pool) => postgres::bind(sqlx::query(&sql), params)
+                .fetch_optional(pool)
+                .await
+                .map_err(query_error)?
+                .as_ref()
+                .map(postgres::decode)
+                .transpose(),
+            #[cfg(feature = "mysql")]
+            Self::MySql(pool) => mysql::bind(sqlx::query(&sql), params)
+                .fetch_optional(pool)
+                .await
+                .map_err(query_error)?
+                .as_ref()
+                .map(mysql::decode)
+                .transpose(),
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(pool) => sqlite::bind(sqlx::query(&sql), params)
+                .fetch_optional(pool)
+                .await
+                .map_err(query_error)?
+                .as_ref()
+                .map(sqlite::decode)
+                .transpose(),
+        }
+    }
+
+    /// Run a query that must return a row
+    pub async fn fetch_one(&self, sql: &str, params: &[DbValue]) -> Result<DbRow> {
+        self.fetch_optional(sql, params)
+            .await?
+            .ok_or_else(|| DatabaseError::Query("Query returned no rows".to_string()))
+    }
+
+    /// Start a transaction on one of the pool's connections
+    pub async fn begin(&self) -> Result<ProviderTransaction> {
+        match self {
+            Self::Postgres(pool) => pool
+                .begin()
+                .await
+                .map(ProviderTransaction::Postgres)
+                .map_err(query_error),
+            #[cfg(feature = "mysql")]
+            Self::MySql(pool) => pool
+                .begin()
+                .await
+                .map(ProviderTransaction::MySql)
+                .map_err(query_error),
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(pool) => pool
+                .begin()
+                .await
+                .map(ProviderTransaction::Sqlite)
+                .map_err(query_error),
+        }
+    }
+
+    /// Close every connection in the pool
+    pub async fn close(&self) {
+        match self {
+            Self::Postgres(pool) => pool.close().await,
+            #[cfg(feature = "mysql")]
+            Self::MySql(pool) => pool.close().await,
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(pool) => pool.close().await,
+        }
+    }
+}
+
+/// A transaction started with [`ProviderPool::begin`]
+///
+/// Queries take the same placeholders and parameters as the pool. Dropping
+/// the transaction without committing rolls it back.
+#[derive(Debug)]
+pub enum ProviderTransaction {
+    Postgres(sqlx::Transaction<'static, sqlx::Postgres>),
+    #[cfg(feature = "mysql")]
+    MySql(sqlx::Transaction<'static, sqlx::MySql>),
+    #[cfg(feature = "sqlite")]
+    Sqlite(sqlx::Transaction<'static, sqlx::Sqlite>),
+}
+
+impl ProviderTransaction {
+    pub fn kind(&self) -> DatabaseKind {
+        match self {
+            Self::Postgres(_) => DatabaseKind::Postgres,
+            #[cfg(feature = "mysql")]
+            Self::MySql(_) => DatabaseKind::MySql,
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(_) => DatabaseKind::Sqlite,
+        }
+    }
+
+    /// Run a statement, returning the number of affected rows
+    pub async fn execute(&mut self, sql: &str, params: &[DbValue]) -> Result<u64> {
+        let (sql, params) = prepare(self.kind(), sql, params)?;
+        match self {
+            Self::Postgres(tx) => postgres::bind(sqlx::query(&sql), params)
+                .execute(&mut **tx)
+                .await
+                .map(|done| done.rows_affected())
+                .map_err(query_error),
+            #[cfg(feature = "mysql")]
+            Self::MySql(tx) => mysql::bind(sqlx::query(&sql), params)
+                .execute(&mut **tx)
+                .await
+                .map(|done| done.rows_affected())
+                .map_err(query_error),
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(tx) => sqlite::bind(sqlx::query(&sql), params)
+                .execute(&mut **tx)
+                .await
+                .map(|done| done.rows_affected())
+                .map_err(query_error),
+        }
+    }
+
+    /// Run a query and decode the first row, if any
+    pub async fn fetch_optional(&mut self, sql: &str, params: &[DbValue]) -> Result<Option<DbRow>> {
+        let (sql, params) = prepare(self.kind(), sql, params)?;
+        match self {
+            Self::Postgres(tx) => postgres::bind(sqlx::query(&sql), params)
+                .fetch_optional(&mut **tx)
+                .await
+                .map_err(query_error)?
+                .as_ref()
+                .map(postgres::decode)
+                .transpose(),
+            #[cfg(feature = "mysql")]
+            Self::MySql(tx) => mysql::bind(sqlx::query(&sql), params)
+                .fetch_optional(&mut **tx)
+                .await
+                .map_err(query_error)?
+                .as_ref()
+                .map(mysql::decode)
+                .transpose(),
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(tx) => sqlite::bind(sqlx::query(&sql), params)
+                .fetch_optional(&mut **tx)
+                .await
+                .map_err(query_error)?
+                .as_ref()
+                .map(sqlite::decode)
+                .transpose(),
+        }
+    }
+
+    pub async fn commit(self) -> Result<()> {
+        match self {
+            Self::Postgres(tx) => tx.commit().await.map_err(query_error),
+            #[cfg(feature = "mysql")]
+            Self::MySql(tx) => tx.commit().await.map_err(query_error),
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(tx) => tx.commit().await.map_err(query_error),
+        }
+    }
+
+    pub async fn rollback(self) -> Result<()> {
+        match self {
+            Self::Postgres(tx) => tx.rollback().await.map_err(query_error),
+            #[cfg(feature = "mysql")]
+            Self::MySql(tx) => tx.rollback().await.map_err(query_error),
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(tx) => tx.rollback().await.map_err(query_error),
+        }
+    }
+}
+
+mod postgres {
+    use super::*;
+    use sqlx::postgres::{PgArguments, PgRow};
+    use sqlx::query::Query;
+    use sqlx::{Column, Postgres, Row, TypeInfo, ValueRef};
+
+    pub(super) fn bind(
+        mut query: Query<'_, Postgres, PgArguments>,
+        params: Vec<DbValue>,
+    ) -> Query<'_, Postgres, PgArguments> {
+        for value in params {
+            query = match value {
+                DbValue::Null => query.bind(None::<String>),
+                DbValue::Bool(b) => query.bind(b),
+                DbValue::Int(i) => query.bind(i),
+                DbValue::Float(f) => query.bind(f),
+                DbValue::Text(s) => query.bind(s),
+                DbValue::Bytes(bytes) => query.bind(bytes),
+                DbValue::Uuid(id) => query.bind(id),
+                DbValue::Timestamp(at) => query.bind(at),
+                DbValue::Json(json) => query.bind(sqlx::types::Json(json)),
+            };
+        }
+        query
+    }
+
+    pub(super) fn decode(row: &PgRow) -> Result<DbRow> {
+        let mut decoded = DbRow::default();
+        for (i, column) in row.columns().iter().enumerate() {
+            if row.try_get_raw(i).map_err(query_error)?.is_null() {
+                decoded.push(column.name(), DbValue::Null);
+                continue;
+            }
+            let value = match column.type_info().name() {
+                "BOOL" => DbValue::Bool(row.try_get(i).map_err(query_error)?),
+                "INT2" => DbValue::Int(row.try_get::<i16, _>(i).map_err(query_error)?.into()),
+                "INT4" => DbValue::Int(row.try_get::<i32, _>(i).map_err(query_error)?.into()),
+                "INT8" => DbValue::Int(row.try_get(i).map_err(query_error)?),
+                "FLOAT4" => DbValue::Float(row.try_get::<f32, _>(i).map_err(query_error)?.into()),
+                "FLOAT8" => DbValue::Float(row.try_get(i).map_err(query_error)?),
+                "UUID" => DbValue::Uuid(row.try_get(i).map_err(query_error)?),
+                "TIMESTAMPTZ" => DbValue::Timestamp(row.try_get(i).map_err(query_error)?),
+                "TIMESTAMP" => DbValue::Timestamp(
+                    row.try_get::<NaiveDateTime, _>(i)
+                        .map_err(query_error)?
+                        .and_utc(),
+                ),
+                "JSON" | "JSONB" => DbValue::Json(row.try_get(i).map_err(query_error)?),
+                "BYTEA" => DbValue::Bytes(row.try_get(i).map_err(query_error)?),
+                _ => DbValue::Text(row.try_get(i).map_err(query_error)?),
+            };
+            decoded.push(column.name(), value);
+        }
+        Ok(decoded)
+    }
+}
+
+#[cfg(feature = "mysql")]
+mod mysql {
+    use super::*;
+    use sqlx::mysql::{MySqlArguments, MySqlRow};
+    use sqlx::query::Query;
+    use sqlx::{Column, MySql, Row, TypeInfo, ValueRef};
+
+    /// UUIDs are stored as `CHAR(36)` in the MySQL schema
+    pub(super) fn bind(
+        mut query: Query<'_, MySql, MySqlArguments>,
+        params: Vec<DbValue>,
+    ) -> Query<'_, MySql, MySqlArguments> {
+        for value in params {
+            query = match value {
+                DbValue::Null => query.bind(None::<String>),
+                DbValue::Bool(b) => query.bind(b),
+                DbValue::Int(i) => query.bind(i),
+                DbValue::Float(f) => query.bind(f),
+                DbValue::Text(s) => query.bind(s),
+                DbValue::Bytes(bytes) => query.bind(bytes),
+                DbValue::Uuid(id) => query.bind(id.hyphenated().to_string()),
+                DbValue::Timestamp(at) => query.bind(at),
+                DbValue::Json(json) => query.bind(sqlx::types::Json(json)),
+            };
+        }
+        query
+    }
+
+    pub(super) fn decode(row: &MySqlRow) -> Result<DbRow> {
+        let mut decoded = DbRow::default();
+        for (i, column) in row.columns().iter().enumerate() {
+            if row.try_get_raw(i).map_err(query_error)?.is_null() {
+                decoded.push(column.name(), DbValue::Null);
+                continue;
+            }
+            let name = column.type_info().name();
+            let value = match name {
+                "BOOLEAN" => DbValue::Bool(row.try_get(i).map_err(query_error)?),
+                _ if name.ends_with("INT UNSIGNED") => DbValue::Int(
+                    i64::try_from(row.try_get::<u64, _>(i).map_err(query_error)?)
+                        .map_err(|e| DatabaseError::Query(e.to_string()))?,
+                ),
+                "TINYINT" | "SMALLINT" | "MEDIUMINT" | "INT" | "BIGINT" => {
+                    DbValue::Int(row.try_get(i).map_err(query_error)?)
+                }
+                "FLOAT" => DbValue::Float(row.try_get::<f32, _>(i).map_err(query_error)?.into()),
+                "DOUBLE" => DbValue::Float(row.try_get(i).map_err(query_error)?),
+                "DATETIME" | "TIMESTAMP" => {
+                    DbValue::Timestamp(row.try_get(i).map_err(query_error)?)
+                }
+                "JSON" => DbValue::Json(row.try_get(i).map_err(query_error)?),
+                "BINARY" | "VARBINARY" | "TINYBLOB" | "BLOB" | "MEDIUMBLOB" | "LONGBLOB" => {
+                    DbValue::Bytes(row.try_get(i).map_err(query_error)?)
+                }
+                _ => DbValue::Text(row.try_get(i).map_err(query_error)?),
+            };
+            decoded.push(column.name(), value);
+        }
+        Ok(decoded)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use super::*;
+    use sqlx::query::Query;
+    use sqlx::sqlite::{SqliteArguments, SqliteRow};
+    use sqlx::{Column, Row, Sqlite, TypeInfo, ValueRef};
+
+    /// UUIDs are stored as text, like the MySQL schema
+    pub(super) fn bind<'q>(
+        mut query: Query<'q, Sqlite, SqliteArguments<'q>>,
+        params: Vec<DbValue>,
+    ) -> Query<'q, Sqlite, SqliteArguments<'q>> {
+        for value in params {
+            query = match value {
+                DbValue::Null => query.bind(None::<String>),
+                DbValue::Bool(b) => query.bind(b),
+                DbValue::Int(i) => query.bind(i),
+                DbValue::Float(f) => query.bind(f),
+                DbValue::Text(s) => query.bind(s),
+                DbValue::Bytes(bytes) => query.bind(bytes),
+                DbValue::Uuid(id) => query.bind(id.hyphenated().to_string()),
+                DbValue::Timestamp(at) => query.bind(at),
+                DbValue::Json(json) => query.bind(sqlx::types::Json(json)),
+            };
+        }
+        query
+    }
+
+    /// Values are decoded by storage class; booleans, timestamps and JSON
+    /// come back as integers and text and are converted on read
+    pub(super) fn decode(row: &SqliteRow) -> Result<DbRow> {
+        let mut decoded = DbRow::default();
+        for (i, column) in row.columns().iter().enumerate() {
+            let raw = row.try_get_raw(i).map_err(query_error)?;
+            if raw.is_null() {
+                decoded.push(column.name(), DbValue::Null);
+                continue;
+            }
+            let storage_class = raw.type_info().name().to_string();
+            let value = match storage_class.as_str() {
+                "INTEGER" => DbValue::Int(row.try_get(i).map_err(query_error)?),
+                "REAL" => DbValue::Float(row.try_get(i).map_err(query_error)?),
+                "BLOB" => DbValue::Bytes(row.try_get(i).map_err(query_error)?),
+                _ => DbValue::Text(row.try_get(i).map_err(query_error)?),
+            };
+            decoded.push(column.name(), value);
+        }
+        Ok(decoded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prepare_rewrites_placeholders() {
+        let params = [DbValue::Int(1), DbValue::from("a"), DbValue::Null];
+        let sql = "SELECT '$1' FROM t WHERE a = $2 AND b = $1 AND c = $3 OR d = $2";
+
+        let (pg, bound) = prepare(DatabaseKind::Postgres, sql, &params).unwrap();
+        assert_eq!(
+            pg,
+            "SELECT '$1' FROM t WHERE a = $1 AND b = $2 AND c = NULL OR d = $1"
+        );
+        assert_eq!(bound, vec![DbValue::from("a"), DbValue::Int(1)]);
+
+        let (lite, bound) = prepare(DatabaseKind::Sqlite, sql, &params).unwrap();
+        assert_eq!(
+            lite,
+            "SELECT '$1' FROM t WHERE a = ?1 AND b = ?2 AND c = NULL OR d = ?1"
+        );
+        assert_eq!(bound.len(), 2);
+
+        let (my, bound) = prepare(DatabaseKind::MySql, sql, &params).unwrap();
+        assert_eq!(
+            my,
+            "SELECT '$1' FROM t WHERE a = ? AND b = ? AND c = NULL OR d = ?"
+        );
+        assert_eq!(
+            bound,
+            vec![DbValue::from("a"), DbValue::Int(1), DbValue::from("a")]
+        );
+
+        assert!(prepare(DatabaseKind::MySql, "SELECT $2", &params[..1]).is_err());
+    }
+
+    #[test]
+    fn test_row_conversions() {
+        let id = Uuid::new_v4();
+        let mut row = DbRow::default();
+        row.push("id", DbValue::Text(id.to_string()));
+        row.push("published", DbValue::Int(1));
+        row.push(
+            "created_at",
+            DbValue::Text("2024-05-01 10:30:00".to_string()),
+        );
+        row.push("meta", DbValue::Text(r#"{"a":1}"#.to_string()));
+        row.push("parent_id", DbValue::Null);
+
+        assert_eq!(row.get::<Uuid>("id").unwrap(), id);
+        assert!(row.get::<bool>("published").unwrap());
+        assert_eq!(
+            row.get::<DateTime<Utc>>("created_at").unwrap().to_rfc3339(),
+            "2024-05-01T10:30:00+00:00"
+        );
+        assert_eq!(row.get::<Value>("meta").unwrap()["a"], 1);
+        assert_eq!(row.get::<Option<Uuid>>("parent_id").unwrap(), None);
+        assert!(row.get::<Uuid>("parent_id").is_err());
+        assert!(row.get::<i64>("missing").is_err());
+        assert_eq!(
+            row.columns().collect::<Vec<_>>(),
+            vec!["id", "published", "created_at", "meta", "parent_id"]
+        );
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_sqlite_round_trip() {
+        let pool = ProviderPool::Sqlite(
+            sqlx::sqlite::SqlitePoolOptions::new()
+                .max_connections(1)
+                .connect("sqlite::memory:")
+                .await
+                .unwrap(),
+        );
+        pool.execute(
+            "CREATE TABLE posts (id TEXT PRIMARY KEY, title TEXT NOT NULL, \
+             views INTEGER, published BOOLEAN, created_at DATETIME, meta TEXT)",
+            &[],
+        )
+        .await
+        .unwrap();
+
+        let id = Uuid::new_v4();
+        let created_at = Utc::now();
+        let inserted = pool
+            .execute(
+                "INSERT INTO posts (id, title, views, published, created_at, meta) \
+                 VALUES ($1, $2, $3, $4, $5, $6)",
+                &[
+                    id.into(),
+                    "Hello".into(),
+                    DbValue::Null,
+                    true.into(),
+                    created_at.into(),
+                    serde_json::json!({"lang": "en"}).into(),
+                ],
+            )
+            .await
+            .unwrap();
+        assert_eq!(inserted, 1);
+
+        let row = pool
+            .fetch_one(
+                "SELECT id, title, views, published, created_at, meta FROM posts WHERE id = $1",
+                &[id.into()],
+            )
+            .await
+            .unwrap();
+        assert_eq!(row.get::<Uuid>("id").unwrap(), id);
+        assert_eq!(row.get::<String>("title").unwrap(), "Hello");
+        assert_eq!(row.get::<Option<i64>>("views").unwrap(), None);
+        assert!(row.get::<bool>("published").unwrap());
+        assert_eq!(row.get::<DateTime<Utc>>("created_at").unwrap(), created_at);
+        assert_eq!(row.get::<Value>("meta").unwrap()["lang"], "en");
+
+        let missing = pool
+            .fetch_optional("SELECT id FROM posts WHERE id = $1", &[Uuid::nil().into()])
+            .await
+            .unwrap();
+        assert!(missing.is_none());
+        assert_eq!(pool.kind(), DatabaseKind::Sqlite);
+        assert!(pool.as_postgres().is_none());
+    }
+}
@@ -30,13 +30,25 @@
 //!   database_password: your-password
 //! ```
 
+pub mod executor;
+#[cfg(feature = "mysql")]
+pub mod mysql;
+#[cfg(feature = "mysql")]
 pub mod planetscale;
 pub mod postgres;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
 pub mod supabase;
 
 // Re-exports
+pub use executor::{DatabaseKind, DbRow, DbValue, FromDbValue, ProviderPool, ProviderTransaction};
+#[cfg(feature = "mysql")]
+pub use mysql::{MysqlConfig as FullMysqlConfig, MysqlProvider, MysqlStats};
+#[cfg(feature = "mysql")]
 pub use planetscale::{PlanetScaleConfig, PlanetScaleProvider};
-pub use postgres::{PostgresConfig as FullPostgresConfig, PostgresProvider, DatabaseStats};
+pub use postgres::{DatabaseStats, PostgresConfig as FullPostgresConfig, PostgresProvider};
+#[cfg(feature = "sqlite")]
+pub use sqlite::{SqliteConfig as FullSqliteConfig, SqliteProvider, SqliteStats};
 pub use supabase::{SupabaseConfig, SupabaseProvider};
 
 use async_trait::async_trait;
@@ -74,7 +86,10 @@ pub trait DatabaseProvider: Send + Sync {
     async fn disconnect(&mut self) -> Result<()>;
 
     /// Get the connection pool (if connected)
-    fn pool(&self) -> Option<&sqlx::PgPool>;
+    ///
+    /// The pool runs the same queries on every backend; see
+    /// [`executor`] for placeholder and row handling.
+    fn pool(&self) -> Option<ProviderPool>;
 
     /// Check if connected and healthy
     async fn health_check(&self) -> Result<bool>;
@@ -95,7 +110,8 @@ pub enum DatabaseConfig {
     #[serde(rename = "supabase")]
     Supabase(SupabaseConfig),
 
-    /// PlanetScale serverless MySQL
+    /// PlanetScale serverless MySQL (requires the `mysql` feature)
+    #[cfg(feature = "mysql")]
     #[serde(rename = "planetscale")]
     PlanetScale(PlanetScaleConfig),
 
@@ -128,8 +144,11 @@ pub struct SqliteConfig {
     pub path: String,
     #[serde(default)]
     pub create_if_missing: bool,
+    /// Lock wait in milliseconds
     #[serde(default)]
     pub busy_timeout: Option<u64>,
+    #[serde(default)]
+    pub migrations_path: Option<std::path::PathBuf>,
 }
 
 /// MySQL configuration
@@ -144,6 +163,14 @@ pub struct MysqlConfig {
     pub ssl_mode: Option<String>,
     #[serde(default)]
     pub pool_size: Option<u32>,
+    /// CA certificate for the verify_* SSL modes
+    #[serde(default)]
+    pub ssl_ca: Option<std::path::PathBuf>,
+    /// Unix socket path, used instead of host and port
+    #[serde(default)]
+    pub socket: Option<std::path::PathBuf>,
+    #[serde(default)]
+    pub migrations_path: Option<std::path::PathBuf>,
 }
 
 /// Database factory for creating providers
@@ -168,27 +195,61 @@ impl DatabaseFactory {
                     ..Default::default()
                 })))
             }
-            DatabaseConfig::Supabase(cfg) => {
-                Ok(Box::new(SupabaseProvider::new(cfg.clone())))
-            }
-            DatabaseConfig::PlanetScale(cfg) => {
-                Ok(Box::new(PlanetScaleProvider::new(cfg.clone())))
+            DatabaseConfig::Supabase(cfg) => Ok(Box::new(SupabaseProvider::new(cfg.clone()))),
+            #[cfg(feature = "mysql")]
+            DatabaseConfig::PlanetScale(cfg) => Ok(Box::new(PlanetScaleProvider::new(cfg.clone()))),
+            #[cfg(feature = "sqlite")]
+            DatabaseConfig::Sqlite(cfg) => {
+                let defaults = sqlite::SqliteConfig::default();
+                Ok(Box::new(SqliteProvider::new(sqlite::SqliteConfig {
+                    path: cfg.path.clone(),
+                    create_if_missing: cfg.create_if_missing,
+                    busy_timeout: cfg.busy_timeout.unwrap_or(defaults.busy_timeout),
+                    migrations_path: cfg.migrations_path.clone(),
+                    ..defaults
+                })))
             }
+            #[cfg(not(feature = "sqlite"))]
             DatabaseConfig::Sqlite(_) => Err(DatabaseError::UnsupportedProvider(
-                "SQLite provider not yet implemented".to_string(),
+                "SQLite support requires the `sqlite` feature".to_string(),
             )),
+            #[cfg(feature = "mysql")]
+            DatabaseConfig::Mysql(cfg) => {
+                let defaults = mysql::MysqlConfig::default();
+                Ok(Box::new(MysqlProvider::new(mysql::MysqlConfig {
+                    host: cfg.host.clone(),
+                    port: cfg.port,
+                    socket: cfg.socket.clone(),
+                    username: cfg.username.clone(),
+                    password: cfg.password.clone(),
+                    database: cfg.database.clone(),
+                    ssl_mode: cfg.ssl_mode.clone().unwrap_or(defaults.ssl_mode),
+                    ssl_ca: cfg.ssl_ca.clone(),
+                    migrations_path: cfg.migrations_path.clone(),
+                    pool: mysql::PoolConfig {
+                        max_connections: cfg.pool_size.unwrap_or(defaults.pool.max_connections),
+                        ..defaults.pool
+                    },
+                    ..defaults
+                })))
+            }
+            #[cfg(not(feature = "mysql"))]
             DatabaseConfig::Mysql(_) => Err(DatabaseError::UnsupportedProvider(
-                "MySQL provider not yet implemented".to_string(),
+                "MySQL support requires the `mysql` feature".to_string(),
             )),
         }
     }
 
     /// Create provider from environment variables
     pub fn from_env() -> Result<Box<dyn DatabaseProvider>> {
-        let provider = std::env::var("DATABASE_PROVIDER")
-            .unwrap_or_else(|_| "postgres".to_string());
+        let provider =
+            std::env::var("DATABASE_PROVIDER").unwrap_or_else(|_| "postgres".to_string());
 
         match provider.as_str() {
+            #[cfg(feature = "sqlite")]
+            "sqlite" => Ok(Box::new(SqliteProvider::from_env()?)),
+            #[cfg(feature = "mysql")]
+            "mysql" | "mariadb" => Ok(Box::new(MysqlProvider::from_env()?)),
             "postgres" | "postgresql" => {
                 // Check if DATABASE_URL is set (common pattern)
                 if let Ok(url) = std::env::var("DATABASE_URL") {
@@ -199,19 +260,16 @@ impl DatabaseFactory {
             }
             "supabase" => {
                 let config = SupabaseConfig {
-                    project_url: std::env::var("SUPABASE_URL")
-                        .map_err(|_| DatabaseError::Configuration(
-                            "SUPABASE_URL not set".to_string()
-                        ))?,
-                    anon_key: std::env::var("SUPABASE_ANON_KEY")
-                        .map_err(|_| DatabaseError::Configuration(
-                            "SUPABASE_ANON_KEY not set".to_string()
-                        ))?,
+                    project_url: std::env::var("SUPABASE_URL").map_err(|_| {
+                        DatabaseError::Configuration("SUPABASE_URL not set".to_string())
+                    })?,
+                    anon_key: std::env::var("SUPABASE_ANON_KEY").map_err(|_| {
+                        DatabaseError::Configuration("SUPABASE_ANON_KEY not set".to_string())
+                    })?,
                     service_role_key: std::env::var("SUPABASE_SERVICE_ROLE_KEY").ok(),
-                    database_password: std::env::var("SUPABASE_DB_PASSWORD")
-                        .map_err(|_| DatabaseError::Configuration(
-                            "SUPABASE_DB_PASSWORD not set".to_string()
-                        ))?,
+                    database_password: std::env::var("SUPABASE_DB_PASSWORD").map_err(|_| {
+                        DatabaseError::Configuration("SUPABASE_DB_PASSWORD not set".to_string())
+                    })?,
                     use_pooler: std::env::var("SUPABASE_USE_POOLER")
                         .map(|v| v == "true")
                         .unwrap_or(true),
@@ -221,30 +279,32 @@ impl DatabaseFactory {
                 };
                 Ok(Box::new(SupabaseProvider::new(config)))
             }
+            #[cfg(feature = "mysql")]
             "planetscale" => {
                 let config = PlanetScaleConfig {
                     host: std::env::var("PLANETSCALE_HOST")
                         .unwrap_or_else(|_| "aws.connect.psdb.cloud".to_string()),
-                    username: std::env::var("PLANETSCALE_USERNAME")
-                        .map_err(|_| DatabaseError::Configuration(
-                            "PLANETSCALE_USERNAME not set".to_string()
-                        ))?,
-                    password: std::env::var("PLANETSCALE_PASSWORD")
-                        .map_err(|_| DatabaseError::Configuration(
-                            "PLANETSCALE_PASSWORD not set".to_string()
-                        ))?,
-                    database: std::env::var("PLANETSCALE_DATABASE")
-                        .map_err(|_| DatabaseError::Configuration(
-                            "PLANETSCALE_DATABASE not set".to_string()
-                        ))?,
+                    username: std::env::var("PLANETSCALE_USERNAME").map_err(|_| {
+                        DatabaseError::Configuration("PLANETSCALE_USERNAME not set".to_string())
+                    })?,
+                    password: std::env::var("PLANETSCALE_PASSWORD").map_err(|_| {
+                        DatabaseError::Configuration("PLANETSCALE_PASSWORD not set".to_string())
+                    })?,
+                    database: std::env::var("PLANETSCALE_DATABASE").map_err(|_| {
+                        DatabaseError::Configuration("PLANETSCALE_DATABASE not set".to_string())
+                    })?,
                     use_ssl: true,
                     pool: planetscale::PoolConfig::default(),
                     branch: std::env::var("PLANETSCALE_BRANCH").ok(),
                 };
                 Ok(Box::new(PlanetScaleProvider::new(config)))
             }
+            #[cfg(not(feature = "mysql"))]
+            "planetscale" => Err(DatabaseError::UnsupportedProvider(
+                "PlanetScale support requires the `mysql` feature".to_string(),
+            )),
             _ => Err(DatabaseError::UnsupportedProvider(format!(
-                "Unknown provider '{}'. Supported: postgres, supabase, planetscale",
+                "Unknown provider '{}'. Supported: postgres, supabase, planetscale, sqlite, mysql",
                 provider
             ))),
         }
@@ -272,4 +332,40 @@ database_password: test-pass
             _ => panic!("Expected Supabase config"),
         }
     }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_sqlite_provider_from_config() {
+        let yaml = r#"
+provider: sqlite
+path: ./data/rustpress.db
+create_if_missing: true
+busy_timeout: 2000
+"#;
+
+        let config: DatabaseConfig = serde_yaml::from_str(yaml).unwrap();
+        let provider = DatabaseFactory::create(&config).unwrap();
+        assert_eq!(provider.provider_name(), "sqlite");
+        assert!(provider.pool().is_none());
+    }
+
+    #[cfg(feature = "mysql")]
+    #[test]
+    fn test_mysql_provider_from_config() {
+        let yaml = r#"
+provider: mysql
+host: localhost
+port: 3306
+username: rustpress
+password: secret
+database: rustpress
+ssl_mode: required
+pool_size: 5
+"#;
+
+        let config: DatabaseConfig = serde_yaml::from_str(yaml).unwrap();
+        let provider = DatabaseFactory::create(&config).unwrap();
+        assert_eq!(provider.provider_name(), "mysql");
+        assert!(provider.pool().is_none());
+    }
 }
@@ -19,9 +19,9 @@ use serde::{Deserialize, Serialize};
 use sqlx::mysql::{MySqlConnectOptions, MySqlPoolOptions, MySqlSslMode};
 use sqlx::MySqlPool;
 use std::time::Duration;
-use tracing::{debug, info, warn};
+use tracing::{info, warn};
 
-use crate::{DatabaseError, DatabaseProvider, Result};
+use super::{DatabaseError, DatabaseProvider, ProviderPool, Result};
 
 /// PlanetScale configuration
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -139,10 +139,7 @@ impl PlanetScaleProvider {
     pub fn connection_string(&self) -> String {
         format!(
             "mysql://{}:{}@{}:3306/{}?ssl-mode=REQUIRED",
-            self.config.username,
-            self.config.password,
-            self.config.host,
-            self.config.database
+            self.config.username, self.config.password, self.config.host, self.config.database
         )
     }
 
@@ -194,8 +191,8 @@ impl DatabaseProvider for PlanetScaleProvider {
         Ok(())
     }
 
-    fn pool(&self) -> Option<&MySqlPool> {
-        self.pool.as_ref()
+    fn pool(&self) -> Option<ProviderPool> {
+        self.pool.clone().map(ProviderPool::MySql)
     }
 
     async fn health_check(&self) -> Result<bool> {
@@ -234,9 +231,10 @@ impl PlanetScaleApiClient {
 
     /// List branches
     pub async fn list_branches(&self) -> Result<Vec<Branch>> {
-        let org = self.organization.as_ref().ok_or_else(|| {
-            DatabaseError::Configuration("Organization not set".to_string())
-        })?;
+        let org = self
+            .organization
+            .as_ref()
+            .ok_or_else(|| DatabaseError::Configuration("Organization not set".to_string()))?;
 
         let client = reqwest::Client::new();
         let response = client
@@ -259,9 +257,10 @@ impl PlanetScaleApiClient {
 
     /// Create a new branch
     pub async fn create_branch(&self, name: &str, parent: &str) -> Result<Branch> {
-        let org = self.organization.as_ref().ok_or_else(|| {
-            DatabaseError::Configuration("Organization not set".to_string())
-        })?;
+        let org = self
+            .organization
+            .as_ref()
+            .ok_or_else(|| DatabaseError::Configuration("Organization not set".to_string()))?;
 
         let client = reqwest::Client::new();
         let response = client
@@ -290,9 +289,10 @@ impl PlanetScaleApiClient {
         branch: &str,
         into_branch: &str,
     ) -> Result<DeployRequest> {
-        let org = self.organization.as_ref().ok_or_else(|| {
-            DatabaseError::Configuration("Organization not set".to_string())
-        })?;
+        let org = self
+            .organization
+            .as_ref()
+            .ok_or_else(|| DatabaseError::Configuration("Organization not set".to_string()))?;
 
         let client = reqwest::Client::new();
         let response = client
@@ -316,13 +316,11 @@ impl PlanetScaleApiClient {
     }
 
     /// Get connection string for branch
-    pub async fn get_branch_connection(
-        &self,
-        branch: &str,
-    ) -> Result<BranchConnection> {
-        let org = self.organization.as_ref().ok_or_else(|| {
-            DatabaseError::Configuration("Organization not set".to_string())
-        })?;
+    pub async fn get_branch_connection(&self, branch: &str) -> Result<BranchConnection> {
+        let org = self
+            .organization
+            .as_ref()
+            .ok_or_else(|| DatabaseError::Configuration("Organization not set".to_string()))?;
 
         let client = reqwest::Client::new();
         let response = client
@@ -431,10 +429,7 @@ impl PlanetScaleMigrator {
 
         // Create development branch
         let branch_name = format!("migration-{}", migration_name);
-        let branch = self
-            .api
-            .create_branch(&branch_name, "main")
-            .await?;
+        let branch = self.api.create_branch(&branch_name, "main").await?;
 
         info!("Created branch: {}", branch.name);
 
@@ -450,8 +445,7 @@ impl PlanetScaleMigrator {
         provider.connect().await?;
 
         if let Some(pool) = provider.pool() {
-            sqlx::query(schema_sql)
-                .execute(pool)
+            pool.execute(schema_sql, &[])
                 .await
                 .map_err(|e| DatabaseError::Migration(e.to_string()))?;
         }
@@ -461,10 +455,7 @@ impl PlanetScaleMigrator {
         info!("Schema changes applied to branch");
 
         // Create deploy request
-        let deploy_request = self
-            .api
-            .create_deploy_request(&branch_name, "main")
-            .await?;
+        let deploy_request = self.api.create_deploy_request(&branch_name, "main").await?;
 
         info!("Deploy request created: {}", deploy_request.id);
 
@@ -33,11 +33,10 @@ use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use sqlx::postgres::{PgConnectOptions, PgPoolOptions, PgSslMode};
 use sqlx::PgPool;
-use std::str::FromStr;
 use std::time::Duration;
 use tracing::{debug, info, warn};
 
-use super::{DatabaseError, DatabaseProvider, Result};
+use super::{DatabaseError, DatabaseProvider, ProviderPool, Result};
 
 /// PostgreSQL configuration
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -317,9 +316,10 @@ impl PostgresProvider {
 
         // Set statement timeout if specified
         if self.config.statement_timeout > 0 {
-            options = options.options([
-                ("statement_timeout", format!("{}s", self.config.statement_timeout)),
-            ]);
+            options = options.options([(
+                "statement_timeout",
+                format!("{}s", self.config.statement_timeout),
+            )]);
         }
 
         options
@@ -327,25 +327,25 @@ impl PostgresProvider {
 
     /// Get database statistics
     pub async fn get_stats(&self) -> Result<DatabaseStats> {
-        let pool = self.pool.as_ref().ok_or_else(|| {
-            DatabaseError::Connection("Not connected to database".to_string())
-        })?;
+        let pool = self
+            .pool
+            .as_ref()
+            .ok_or_else(|| DatabaseError::Connection("Not connected to database".to_string()))?;
 
         // Get connection pool stats
         let pool_size = pool.size();
         let idle_connections = pool.num_idle();
 
         // Get database size
-        let db_size: Option<i64> = sqlx::query_scalar(
-            "SELECT pg_database_size(current_database())"
-        )
-        .fetch_optional(pool)
-        .await
-        .map_err(|e| DatabaseError::Query(e.to_string()))?;
+        let db_size: Option<i64> =
+            sqlx::query_scalar("SELECT pg_database_size(current_database())")
+                .fetch_optional(pool)
+                .await
+                .map_err(|e| DatabaseError::Query(e.to_string()))?;
 
         // Get connection count
         let active_connections: Option<i64> = sqlx::query_scalar(
-            "SELECT count(*) FROM pg_stat_activity WHERE datname = current_database()"
+            "SELECT count(*) FROM pg_stat_activity WHERE datname = current_database()",
         )
         .fetch_optional(pool)
         .await
@@ -359,7 +359,7 @@ impl PostgresProvider {
 
         Ok(DatabaseStats {
             pool_size,
-            idle_connections,
+            idle_connections: idle_connections as u32,
             database_size_bytes: db_size.unwrap_or(0),
             active_connections: active_connections.unwrap_or(0) as u32,
             version: version.unwrap_or_else(|| "Unknown".to_string()),
@@ -368,9 +368,10 @@ impl PostgresProvider {
 
     /// Run a raw SQL query (for admin operations)
     pub async fn execute_raw(&self, sql: &str) -> Result<u64> {
-        let pool = self.pool.as_ref().ok_or_else(|| {
-            DatabaseError::Connection("Not connected to database".to_string())
-        })?;
+        let pool = self
+            .pool
+            .as_ref()
+            .ok_or_else(|| DatabaseError::Connection("Not connected to database".to_string()))?;
 
         let result = sqlx::query(sql)
             .execute(pool)
@@ -382,9 +383,10 @@ impl PostgresProvider {
 
     /// Vacuum the database (cleanup dead tuples)
     pub async fn vacuum(&self, full: bool) -> Result<()> {
-        let pool = self.pool.as_ref().ok_or_else(|| {
-            DatabaseError::Connection("Not connected to database".to_string())
-        })?;
+        let pool = self
+            .pool
+            .as_ref()
+            .ok_or_else(|| DatabaseError::Connection("Not connected to database".to_string()))?;
 
         let sql = if full { "VACUUM FULL" } else { "VACUUM" };
 
@@ -399,9 +401,10 @@ impl PostgresProvider {
 
     /// Analyze the database (update statistics)
     pub async fn analyze(&self) -> Result<()> {
-        let pool = self.pool.as_ref().ok_or_else(|| {
-            DatabaseError::Connection("Not connected to database".to_string())
-        })?;
+        let pool = self
+            .pool
+            .as_ref()
+            .ok_or_else(|| DatabaseError::Connection("Not connected to database".to_string()))?;
 
         sqlx::query("ANALYZE")
             .execute(pool)
@@ -414,9 +417,10 @@ impl PostgresProvider {
 
     /// Reindex the database
     pub async fn reindex(&self) -> Result<()> {
-        let pool = self.pool.as_ref().ok_or_else(|| {
-            DatabaseError::Connection("Not connected to database".to_string())
-        })?;
+        let pool = self
+            .pool
+            .as_ref()
+            .ok_or_else(|| DatabaseError::Connection("Not connected to database".to_string()))?;
 
         let db_name = &self.config.database;
         sqlx::query(&format!("REINDEX DATABASE \"{}\"", db_name))
@@ -474,8 +478,8 @@ impl DatabaseProvider for PostgresProvider {
         Ok(())
     }
 
-    fn pool(&self) -> Option<&PgPool> {
-        self.pool.as_ref()
+    fn pool(&self) -> Option<ProviderPool> {
+        self.pool.clone().map(ProviderPool::Postgres)
     }
 
     async fn health_check(&self) -> Result<bool> {
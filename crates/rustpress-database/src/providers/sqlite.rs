@@ -0,0 +1,565 @@
+//! SQLite Database Provider
+//!
+//! Embedded SQLite database for small sites and test runs that should not
+//! need a database server. Connections use WAL journaling so readers do
+//! not block the writer, and a busy timeout so concurrent writers wait for
+//! the lock instead of failing immediately.
+//!
+//! # Configuration
+//!
+//! ```yaml
+//! database:
+//!   provider: sqlite
+//!   path: ./data/rustpress.db
+//!   create_if_missing: true
+//!   busy_timeout: 5000  # milliseconds
+//! ```
+//!
+//! Or via environment variables:
+//! ```bash
+//! DATABASE_PROVIDER=sqlite
+//! SQLITE_PATH=./data/rustpress.db
+//! SQLITE_BUSY_TIMEOUT=5000
+//! SQLITE_MIGRATIONS=./migrations/sqlite
+//! ```
+//!
+//! `path: ":memory:"` opens a private in-memory database, which is handy
+//! for tests. The pool is then limited to a single connection that is kept
+//! open, since every SQLite connection to `:memory:` sees its own database.
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sqlx::migrate::Migrator;
+use sqlx::sqlite::{
+    SqliteConnectOptions, SqliteJournalMode, SqlitePool, SqlitePoolOptions, SqliteSynchronous,
+};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+use super::{DatabaseError, DatabaseProvider, ProviderPool, Result};
+
+/// Path that opens an in-memory database
+pub const MEMORY_PATH: &str = ":memory:";
+
+/// SQLite configuration
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SqliteConfig {
+    /// Database file path, or `:memory:`
+    #[serde(default = "default_path")]
+    pub path: String,
+
+    /// Create the database file if it does not exist
+    #[serde(default = "default_create_if_missing")]
+    pub create_if_missing: bool,
+
+    /// How long a connection waits for a lock, in milliseconds
+    #[serde(default = "default_busy_timeout")]
+    pub busy_timeout: u64,
+
+    /// Journal mode: wal, delete, truncate, persist, memory, off
+    #[serde(default = "default_journal_mode")]
+    pub journal_mode: String,
+
+    /// Synchronous mode: off, normal, full, extra
+    #[serde(default = "default_synchronous")]
+    pub synchronous: String,
+
+    /// Enforce foreign key constraints
+    #[serde(default = "default_foreign_keys")]
+    pub foreign_keys: bool,
+
+    /// Directory of SQLite migrations applied on connect
+    #[serde(default)]
+    pub migrations_path: Option<PathBuf>,
+
+    /// Connection pool settings
+    #[serde(default)]
+    pub pool: PoolConfig,
+}
+
+fn default_path() -> String {
+    "rustpress.db".to_string()
+}
+
+fn default_create_if_missing() -> bool {
+    true
+}
+
+fn default_busy_timeout() -> u64 {
+    5000
+}
+
+fn default_journal_mode() -> String {
+    "wal".to_string()
+}
+
+fn default_synchronous() -> String {
+    // Durable across application crashes in WAL mode; only a power loss
+    // can roll back the most recent commits
+    "normal".to_string()
+}
+
+fn default_foreign_keys() -> bool {
+    true
+}
+
+impl Default for SqliteConfig {
+    fn default() -> Self {
+        Self {
+            path: default_path(),
+            create_if_missing: default_create_if_missing(),
+            busy_timeout: default_busy_timeout(),
+            journal_mode: default_journal_mode(),
+            synchronous: default_synchronous(),
+            foreign_keys: default_foreign_keys(),
+            migrations_path: None,
+            pool: PoolConfig::default(),
+        }
+    }
+}
+
+impl SqliteConfig {
+    /// Create configuration from environment variables
+    pub fn from_env() -> Result<Self> {
+        let path = match std::env::var("SQLITE_PATH") {
+            Ok(path) => path,
+            Err(_) => match std::env::var("DATABASE_URL") {
+                Ok(url) => return Self::from_url(&url),
+                Err(_) => default_path(),
+            },
+        };
+
+        Ok(Self {
+            path,
+            busy_timeout: std::env::var("SQLITE_BUSY_TIMEOUT")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(default_busy_timeout()),
+            migrations_path: std::env::var("SQLITE_MIGRATIONS").ok().map(PathBuf::from),
+            ..Default::default()
+        })
+    }
+
+    /// Parse a `sqlite:` URL such as `sqlite://data/rustpress.db` or
+    /// `sqlite::memory:`
+    pub fn from_url(url: &str) -> Result<Self> {
+        let rest = url
+            .strip_prefix("sqlite:")
+            .ok_or_else(|| DatabaseError::Configuration(format!("Not a SQLite URL: {}", url)))?;
+        let rest = rest.strip_prefix("//").unwrap_or(rest);
+        let path = rest.split('?').next().unwrap_or_default();
+
+        if path.is_empty() {
+            return Err(DatabaseError::Configuration(
+                "SQLite URL has no database path".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            path: path.to_string(),
+            ..Default::default()
+        })
+    }
+
+    /// Whether this configuration opens an in-memory database
+    pub fn is_memory(&self) -> bool {
+        self.path == MEMORY_PATH
+    }
+}
+
+/// Connection pool configuration
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PoolConfig {
+    /// Minimum connections
+    #[serde(default = "default_min_connections")]
+    pub min_connections: u32,
+
+    /// Maximum connections
+    #[serde(default = "default_max_connections")]
+    pub max_connections: u32,
+
+    /// Acquire timeout in seconds
+    #[serde(default = "default_acquire_timeout")]
+    pub acquire_timeout: u64,
+}
+
+fn default_min_connections() -> u32 {
+    1
+}
+
+fn default_max_connections() -> u32 {
+    // SQLite has a single writer; a few readers is all WAL can use
+    5
+}
+
+fn default_acquire_timeout() -> u64 {
+    30
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self {
+            min_connections: default_min_connections(),
+            max_connections: default_max_connections(),
+            acquire_timeout: default_acquire_timeout(),
+        }
+    }
+}
+
+/// SQLite database provider
+pub struct SqliteProvider {
+    config: SqliteConfig,
+    pool: Option<SqlitePool>,
+}
+
+impl SqliteProvider {
+    /// Create a new SQLite provider
+    pub fn new(config: SqliteConfig) -> Self {
+        Self { config, pool: None }
+    }
+
+    /// Create provider from environment variables
+    pub fn from_env() -> Result<Self> {
+        Ok(Self::new(SqliteConfig::from_env()?))
+    }
+
+    /// Create provider from a `sqlite:` URL
+    pub fn from_url(url: &str) -> Result<Self> {
+        Ok(Self::new(SqliteConfig::from_url(url)?))
+    }
+
+    /// Get the connection pool (if connected)
+    pub fn get_pool(&self) -> Option<&SqlitePool> {
+        self.pool.as_ref()
+    }
+
+    fn connected_pool(&self) -> Result<&SqlitePool> {
+        self.pool
+            .as_ref()
+            .ok_or_else(|| DatabaseError::Connection("Not connected to database".to_string()))
+    }
+
+    /// Build connection options
+    fn build_connect_options(&self) -> Result<SqliteConnectOptions> {
+        let journal_mode =
+            SqliteJournalMode::from_str(&self.config.journal_mode).map_err(|_| {
+                DatabaseError::Configuration(format!(
+                    "Invalid journal mode '{}'",
+                    self.config.journal_mode
+                ))
+            })?;
+        let synchronous = SqliteSynchronous::from_str(&self.config.synchronous).map_err(|_| {
+            DatabaseError::Configuration(format!(
+                "Invalid synchronous mode '{}'",
+                self.config.synchronous
+            ))
+        })?;
+
+        let options = if self.config.is_memory() {
+            SqliteConnectOptions::from_str("sqlite::memory:")
+                .map_err(|e| DatabaseError::Configuration(e.to_string()))?
+                // WAL needs a file; memory databases only support MEMORY
+                .journal_mode(SqliteJournalMode::Memory)
+        } else {
+            SqliteConnectOptions::new()
+                .filename(&self.config.path)
+                .create_if_missing(self.config.create_if_missing)
+                .journal_mode(journal_mode)
+        };
+
+        Ok(options
+            .synchronous(synchronous)
+            .busy_timeout(Duration::from_millis(self.config.busy_timeout))
+            .foreign_keys(self.config.foreign_keys))
+    }
+
+    /// Build pool options; an in-memory database lives as long as its
+    /// single connection, so that connection is never recycled
+    fn build_pool_options(&self) -> SqlitePoolOptions {
+        let options = SqlitePoolOptions::new()
+            .acquire_timeout(Duration::from_secs(self.config.pool.acquire_timeout));
+
+        if self.config.is_memory() {
+            options
+                .min_connections(1)
+                .max_connections(1)
+                .idle_timeout(None)
+                .max_lifetime(None)
+        } else {
+            options
+                .min_connections(self.config.pool.min_connections)
+                .max_connections(self.config.pool.max_connections)
+        }
+    }
+
+    /// Apply the migrations in a directory
+    pub async fn run_migrations(&self, path: &Path) -> Result<()> {
+        let pool = self.connected_pool()?;
+
+        let migrator = Migrator::new(path).await.map_err(|e| {
+            DatabaseError::Migration(format!(
+                "Failed to load migrations from {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        migrator
+            .run(pool)
+            .await
+            .map_err(|e| DatabaseError::Migration(e.to_string()))?;
+
+        info!("SQLite migrations from {} applied", path.display());
+        Ok(())
+    }
+
+    /// Get database statistics
+    pub async fn get_stats(&self) -> Result<SqliteStats> {
+        let pool = self.connected_pool()?;
+
+        let page_count: i64 = sqlx::query_scalar("PRAGMA page_count")
+            .fetch_one(pool)
+            .await
+            .map_err(|e| DatabaseError::Query(e.to_string()))?;
+        let page_size: i64 = sqlx::query_scalar("PRAGMA page_size")
+            .fetch_one(pool)
+            .await
+            .map_err(|e| DatabaseError::Query(e.to_string()))?;
+        let journal_mode: String = sqlx::query_scalar("PRAGMA journal_mode")
+            .fetch_one(pool)
+            .await
+            .map_err(|e| DatabaseError::Query(e.to_string()))?;
+        let version: String = sqlx::query_scalar("SELECT sqlite_version()")
+            .fetch_one(pool)
+            .await
+            .map_err(|e| DatabaseError::Query(e.to_string()))?;
+
+        Ok(SqliteStats {
+            pool_size: pool.size(),
+            idle_connections: pool.num_idle() as u32,
+            database_size_bytes: page_count * page_size,
+            journal_mode,
+            version,
+        })
+    }
+
+    /// Run a raw SQL statement (for admin operations)
+    pub async fn execute_raw(&self, sql: &str) -> Result<u64> {
+        let pool = self.connected_pool()?;
+
+        let result = sqlx::query(sql)
+            .execute(pool)
+            .await
+            .map_err(|e| DatabaseError::Query(e.to_string()))?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Rebuild the database file, reclaiming free pages
+    pub async fn vacuum(&self) -> Result<()> {
+        self.execute_raw("VACUUM").await?;
+        info!("SQLite vacuum completed");
+        Ok(())
+    }
+
+    /// Refresh query planner statistics
+    pub async fn analyze(&self) -> Result<()> {
+        self.execute_raw("PRAGMA optimize").await?;
+        info!("SQLite optimize completed");
+        Ok(())
+    }
+
+    /// Copy the WAL into the database file and truncate it
+    pub async fn checkpoint(&self) -> Result<()> {
+        self.execute_raw("PRAGMA wal_checkpoint(TRUNCATE)").await?;
+        debug!("SQLite WAL checkpoint completed");
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl DatabaseProvider for SqliteProvider {
+    async fn connect(&mut self) -> Result<()> {
+        info!("Opening SQLite database at {}...", self.config.path);
+
+        if let Some(parent) = Path::new(&self.config.path).parent() {
+            if self.config.create_if_missing
+                && !self.config.is_memory()
+                && !parent.as_os_str().is_empty()
+            {
+                tokio::fs::create_dir_all(parent).await.map_err(|e| {
+                    DatabaseError::Connection(format!(
+                        "Failed to create database directory {}: {}",
+                        parent.display(),
+                        e
+                    ))
+                })?;
+            }
+        }
+
+        let options = self.build_connect_options()?;
+        let pool = self
+            .build_pool_options()
+            .connect_with(options)
+            .await
+            .map_err(|e| DatabaseError::Connection(format!("Failed to open database: {}", e)))?;
+
+        // Test the connection
+        sqlx::query("SELECT 1")
+            .execute(&pool)
+            .await
+            .map_err(|e| DatabaseError::Connection(format!("Connection test failed: {}", e)))?;
+
+        self.pool = Some(pool);
+
+        if let Some(path) = self.config.migrations_path.clone() {
+            self.run_migrations(&path).await?;
+        }
+
+        info!("Opened SQLite database '{}' successfully", self.config.path);
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> Result<()> {
+        if let Some(pool) = self.pool.take() {
+            info!("Closing SQLite connection pool...");
+            pool.close().await;
+            info!("SQLite connection pool closed");
+        }
+        Ok(())
+    }
+
+    fn pool(&self) -> Option<ProviderPool> {
+        self.pool.clone().map(ProviderPool::Sqlite)
+    }
+
+    async fn health_check(&self) -> Result<bool> {
+        if let Some(pool) = &self.pool {
+            match sqlx::query("SELECT 1").execute(pool).await {
+                Ok(_) => {
+                    debug!("SQLite health check passed");
+                    Ok(true)
+                }
+                Err(e) => {
+                    warn!("SQLite health check failed: {}", e);
+                    Ok(false)
+                }
+            }
+        } else {
+            debug!("SQLite health check: not connected");
+            Ok(false)
+        }
+    }
+
+    fn provider_name(&self) -> &str {
+        "sqlite"
+    }
+}
+
+/// SQLite database statistics
+#[derive(Debug, Clone, Serialize)]
+pub struct SqliteStats {
+    /// Number of connections in the pool
+    pub pool_size: u32,
+    /// Number of idle connections
+    pub idle_connections: u32,
+    /// Database size in bytes
+    pub database_size_bytes: i64,
+    /// Active journal mode
+    pub journal_mode: String,
+    /// SQLite library version
+    pub version: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_from_url() {
+        let config = SqliteConfig::from_url("sqlite://data/rustpress.db?mode=rwc").unwrap();
+        assert_eq!(config.path, "data/rustpress.db");
+        assert_eq!(config.journal_mode, "wal");
+        assert!(config.create_if_missing);
+
+        let config = SqliteConfig::from_url("sqlite::memory:").unwrap();
+        assert!(config.is_memory());
+
+        assert!(SqliteConfig::from_url("postgres://localhost/db").is_err());
+        assert!(SqliteConfig::from_url("sqlite://").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_memory_database() {
+        let mut provider = SqliteProvider::new(SqliteConfig {
+            path: MEMORY_PATH.to_string(),
+            ..Default::default()
+        });
+        assert!(!provider.health_check().await.unwrap());
+
+        provider.connect().await.unwrap();
+        assert!(provider.health_check().await.unwrap());
+
+        provider
+            .execute_raw("CREATE TABLE posts (id INTEGER PRIMARY KEY, title TEXT NOT NULL)")
+            .await
+            .unwrap();
+        let inserted = provider
+            .execute_raw("INSERT INTO posts (title) VALUES ('Hello'), ('World')")
+            .await
+            .unwrap();
+        assert_eq!(inserted, 2);
+
+        // The same pool through the backend-neutral trait
+        let pool = provider.pool().unwrap();
+        let row = pool
+            .fetch_one(
+                "SELECT COUNT(*) AS total FROM posts WHERE title <> $1",
+                &["World".into()],
+            )
+            .await
+            .unwrap();
+        assert_eq!(row.get::<i64>("total").unwrap(), 1);
+
+        let stats = provider.get_stats().await.unwrap();
+        assert_eq!(stats.journal_mode, "memory");
+        assert_eq!(stats.pool_size, 1);
+
+        provider.disconnect().await.unwrap();
+        assert!(provider.get_pool().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_file_database_uses_wal_and_migrations() {
+        let dir = std::env::temp_dir().join(format!("rustpress-sqlite-{}", uuid::Uuid::new_v4()));
+        let migrations = dir.join("migrations");
+        std::fs::create_dir_all(&migrations).unwrap();
+        std::fs::write(
+            migrations.join("0001_create_options.sql"),
+            "CREATE TABLE options (name TEXT PRIMARY KEY, value TEXT);",
+        )
+        .unwrap();
+
+        let mut provider = SqliteProvider::new(SqliteConfig {
+            path: dir.join("db/site.db").to_string_lossy().into_owned(),
+            migrations_path: Some(migrations),
+            ..Default::default()
+        });
+        provider.connect().await.unwrap();
+
+        let stats = provider.get_stats().await.unwrap();
+        assert_eq!(stats.journal_mode, "wal");
+        assert_eq!(
+            provider
+                .execute_raw("INSERT INTO options (name, value) VALUES ('blogname', 'Test')")
+                .await
+                .unwrap(),
+            1
+        );
+        provider.checkpoint().await.unwrap();
+
+        provider.disconnect().await.unwrap();
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
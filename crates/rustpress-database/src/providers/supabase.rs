@@ -18,9 +18,9 @@ use serde::{Deserialize, Serialize};
 use sqlx::postgres::{PgConnectOptions, PgPoolOptions, PgSslMode};
 use sqlx::PgPool;
 use std::time::Duration;
-use tracing::{debug, info, warn};
+use tracing::{info, warn};
 
-use crate::{DatabaseError, DatabaseProvider, Result};
+use super::{DatabaseError, DatabaseProvider, ProviderPool, Result};
 
 /// Supabase configuration
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -140,9 +140,7 @@ impl SupabaseProvider {
 
         let host = if self.config.use_pooler {
             // Supavisor connection pooler
-            format!(
-                "aws-0-us-east-1.pooler.supabase.com"
-            )
+            format!("aws-0-us-east-1.pooler.supabase.com")
         } else {
             // Direct connection
             format!("db.{}.supabase.co", project_ref)
@@ -272,8 +270,8 @@ impl DatabaseProvider for SupabaseProvider {
         Ok(())
     }
 
-    fn pool(&self) -> Option<&PgPool> {
-        self.pool.as_ref()
+    fn pool(&self) -> Option<ProviderPool> {
+        self.pool.clone().map(ProviderPool::Postgres)
     }
 
     async fn health_check(&self) -> Result<bool> {
@@ -314,9 +312,7 @@ impl SupabaseRestClient {
         let url = format!("{}/{}", self.base_url, path);
 
         let key = if use_service_role {
-            self.service_role_key
-                .as_ref()
-                .unwrap_or(&self.anon_key)
+            self.service_role_key.as_ref().unwrap_or(&self.anon_key)
         } else {
             &self.anon_key
         };
@@ -344,11 +340,7 @@ impl SupabaseRestClient {
     }
 
     /// Insert into table
-    pub async fn insert(
-        &self,
-        table: &str,
-        data: serde_json::Value,
-    ) -> Result<serde_json::Value> {
+    pub async fn insert(&self, table: &str, data: serde_json::Value) -> Result<serde_json::Value> {
         let response = self
             .request(reqwest::Method::POST, table, true)
             .await?
@@ -374,11 +366,7 @@ pub struct SupabaseAuthClient {
 
 impl SupabaseAuthClient {
     /// Sign up a new user
-    pub async fn sign_up(
-        &self,
-        email: &str,
-        password: &str,
-    ) -> Result<serde_json::Value> {
+    pub async fn sign_up(&self, email: &str, password: &str) -> Result<serde_json::Value> {
         let client = reqwest::Client::new();
 
         let response = client
@@ -399,11 +387,7 @@ impl SupabaseAuthClient {
     }
 
     /// Sign in with email/password
-    pub async fn sign_in(
-        &self,
-        email: &str,
-        password: &str,
-    ) -> Result<serde_json::Value> {
+    pub async fn sign_in(&self, email: &str, password: &str) -> Result<serde_json::Value> {
         let client = reqwest::Client::new();
 
         let response = client
@@ -454,7 +438,10 @@ impl SupabaseStorageClient {
             .map_err(|e| DatabaseError::Query(e.to_string()))?;
 
         if response.status().is_success() {
-            Ok(format!("{}/object/public/{}/{}", self.base_url, bucket, path))
+            Ok(format!(
+                "{}/object/public/{}/{}",
+                self.base_url, bucket, path
+            ))
         } else {
             Err(DatabaseError::Query("Upload failed".to_string()))
         }
@@ -1,8 +1,10 @@
 //! Generic repository implementations for database operations.
 
+use crate::providers::{DatabaseError, DatabaseKind, DbRow, DbValue, ProviderPool};
 use rustpress_core::error::{Error, Result};
 use rustpress_core::id::TenantId;
 use rustpress_core::service::{ListParams, ListResult, SortOrder};
+use rustpress_core::tenant::current_site;
 use sqlx::PgPool;
 use std::marker::PhantomData;
 use uuid::Uuid;
@@ -99,6 +101,15 @@ impl QueryHelper {
         }
     }
 
+    /// Case-insensitive LIKE operator for a backend; MySQL and SQLite
+    /// already compare case-insensitively with plain LIKE
+    pub fn case_insensitive_like(kind: DatabaseKind) -> &'static str {
+        match kind {
+            DatabaseKind::Postgres => "ILIKE",
+            DatabaseKind::MySql | DatabaseKind::Sqlite => "LIKE",
+        }
+    }
+
     /// Escape string for LIKE
     pub fn escape_like(s: &str) -> String {
         s.replace('\\', "\\\\")
@@ -107,6 +118,139 @@ impl QueryHelper {
     }
 }
 
+/// Optimistic concurrency helpers for tables with a `version` column.
+///
+/// Versioned updates bump `version` and only apply when the stored version
+/// matches the one the caller read. An update that matches no rows is then
+/// resolved into either a not found or a version conflict error carrying
+/// the latest version.
+pub struct Versioning;
+
+impl Versioning {
+    /// Build the guard condition for an expected version bound at `$param`.
+    ///
+    /// A NULL expected version skips the check.
+    pub fn guard(param: usize) -> String {
+        format!("(${0}::BIGINT IS NULL OR version = ${0})", param)
+    }
+
+    /// Build the error for an update that matched no rows
+    pub fn conflict_error(entity_type: &str, id: &str, current_version: Option<i64>) -> Error {
+        match current_version {
+            Some(version) => Error::version_conflict(entity_type, id, version),
+            None => Error::not_found(entity_type, id),
+        }
+    }
+
+    /// Look up the current version of a row and build the matching error.
+    ///
+    /// `scope` is an extra SQL condition (e.g. the site filter) applied to
+    /// the lookup.
+    pub async fn resolve_conflict<K>(
+        pool: &PgPool,
+        entity_type: &str,
+        table: &str,
+        key_column: &str,
+        key: K,
+        scope: &str,
+    ) -> Error
+    where
+        K: for<'q> sqlx::Encode<'q, sqlx::Postgres>
+            + sqlx::Type<sqlx::Postgres>
+            + Send
+            + ToString
+            + 'static,
+    {
+        let id = key.to_string();
+        let query = format!(
+            "SELECT version FROM {} WHERE {} = $1 AND {}",
+            table, key_column, scope
+        );
+
+        match sqlx::query_as::<_, (i64,)>(&query)
+            .bind(key)
+            .fetch_optional(pool)
+            .await
+        {
+            Ok(current) => Self::conflict_error(entity_type, &id, current.map(|(v,)| v)),
+            Err(e) => Error::database_with_source("Failed to read current version", e),
+        }
+    }
+
+    /// Guard for queries run through a [`ProviderPool`].
+    ///
+    /// The pool inlines a NULL expected version, so unlike
+    /// [`guard`](Self::guard) the parameter needs no cast.
+    pub fn provider_guard(param: usize) -> String {
+        format!("(${0} IS NULL OR version = ${0})", param)
+    }
+
+    /// [`resolve_conflict`](Self::resolve_conflict) for a [`ProviderPool`]
+    pub async fn resolve_provider_conflict<K>(
+        pool: &ProviderPool,
+        entity_type: &str,
+        table: &str,
+        key_column: &str,
+        key: K,
+        scope: &str,
+    ) -> Error
+    where
+        K: Into<DbValue> + ToString,
+    {
+        let id = key.to_string();
+        let query = format!(
+            "SELECT version FROM {} WHERE {} = $1 AND {}",
+            table, key_column, scope
+        );
+
+        match pool
+            .fetch_optional(&query, &[key.into()])
+            .await
+            .and_then(|row| row.map(|row| row.get::<i64>("version")).transpose())
+        {
+            Ok(current) => Self::conflict_error(entity_type, &id, current),
+            Err(e) => Error::database_with_source("Failed to read current version", e),
+        }
+    }
+}
+
+/// Rows read through a [`ProviderPool`]
+trait FromDbRow: Sized {
+    fn from_db_row(row: &DbRow) -> std::result::Result<Self, DatabaseError>;
+}
+
+fn decode_optional<T: FromDbRow>(
+    row: Option<DbRow>,
+) -> std::result::Result<Option<T>, DatabaseError> {
+    row.as_ref().map(T::from_db_row).transpose()
+}
+
+fn decode_all<T: FromDbRow>(rows: Vec<DbRow>) -> std::result::Result<Vec<T>, DatabaseError> {
+    rows.iter().map(T::from_db_row).collect()
+}
+
+/// Run an INSERT or UPDATE and return the row it wrote.
+///
+/// MySQL has no `RETURNING`, so there the row is read back with `select`,
+/// which may refer to any of the write's parameters.
+async fn write_returning(
+    pool: &ProviderPool,
+    write: &str,
+    columns: &str,
+    select: &str,
+    params: &[DbValue],
+) -> std::result::Result<Option<DbRow>, DatabaseError> {
+    if pool.kind() == DatabaseKind::MySql {
+        if pool.execute(write, params).await? == 0 {
+            return Ok(None);
+        }
+        pool.fetch_optional(select, params).await
+    } else {
+        pool.fetch_optional(&format!("{} RETURNING {}", write, columns), params)
+            .await
+    }
+}
+
 /// User repository implementation
 pub mod users {
     use super::*;
@@ -131,77 +275,117 @@ pub mod users {
         pub deleted_at: Option<DateTime<Utc>>,
     }
 
+    impl FromDbRow for UserRow {
+        fn from_db_row(row: &DbRow) -> std::result::Result<Self, DatabaseError> {
+            Ok(Self {
+                id: row.get("id")?,
+                email: row.get("email")?,
+                username: row.get("username")?,
+                password_hash: row.get("password_hash")?,
+                display_name: row.get("display_name")?,
+                status: row.get("status")?,
+                role: row.get("role")?,
+                avatar_url: row.get("avatar_url")?,
+                locale: row.get("locale")?,
+                timezone: row.get("timezone")?,
+                email_verified_at: row.get("email_verified_at")?,
+                last_login_at: row.get("last_login_at")?,
+                created_at: row.get("created_at")?,
+                updated_at: row.get("updated_at")?,
+                deleted_at: row.get("deleted_at")?,
+            })
+        }
+    }
+
     pub struct UserRepository {
-        pool: PgPool,
+        pool: ProviderPool,
     }
 
     impl UserRepository {
         pub fn new(pool: PgPool) -> Self {
+            Self::from_provider(ProviderPool::Postgres(pool))
+        }
+
+        /// Repository on whichever backend the provider connected to
+        pub fn from_provider(pool: ProviderPool) -> Self {
             Self { pool }
         }
 
         pub async fn find_by_email(&self, email: &str) -> Result<Option<UserRow>> {
-            sqlx::query_as::<_, UserRow>(
-                "SELECT * FROM users WHERE email = $1 AND deleted_at IS NULL",
-            )
-            .bind(email)
-            .fetch_optional(&self.pool)
-            .await
-            .map_err(|e| Error::database_with_source("Failed to find user by email", e))
+            self.pool
+                .fetch_optional(
+                    "SELECT * FROM users WHERE email = $1 AND deleted_at IS NULL",
+                    &[email.into()],
+                )
+                .await
+                .and_then(decode_optional)
+                .map_err(|e| Error::database_with_source("Failed to find user by email", e))
         }
 
         pub async fn find_by_username(&self, username: &str) -> Result<Option<UserRow>> {
-            sqlx::query_as::<_, UserRow>(
-                "SELECT * FROM users WHERE username = $1 AND deleted_at IS NULL",
-            )
-            .bind(username)
-            .fetch_optional(&self.pool)
-            .await
-            .map_err(|e| Error::database_with_source("Failed to find user by username", e))
+            self.pool
+                .fetch_optional(
+                    "SELECT * FROM users WHERE username = $1 AND deleted_at IS NULL",
+                    &[username.into()],
+                )
+                .await
+                .and_then(decode_optional)
+                .map_err(|e| Error::database_with_source("Failed to find user by username", e))
         }
 
         pub async fn create(&self, user: &UserRow) -> Result<UserRow> {
-            sqlx::query_as::<_, UserRow>(
+            let params = [
+                user.id.into(),
+                user.email.as_str().into(),
+                user.username.as_str().into(),
+                user.password_hash.as_str().into(),
+                user.display_name.clone().into(),
+                user.status.as_str().into(),
+                user.role.as_str().into(),
+                user.avatar_url.clone().into(),
+                user.locale.clone().into(),
+                user.timezone.clone().into(),
+                user.created_at.into(),
+                user.updated_at.into(),
+            ];
+
+            write_returning(
+                &self.pool,
                 r#"
                 INSERT INTO users (id, email, username, password_hash, display_name, status, role, avatar_url, locale, timezone, created_at, updated_at)
                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
-                RETURNING *
                 "#,
+                "*",
+                "SELECT * FROM users WHERE id = $1",
+                &params,
             )
-            .bind(user.id)
-            .bind(&user.email)
-            .bind(&user.username)
-            .bind(&user.password_hash)
-            .bind(&user.display_name)
-            .bind(&user.status)
-            .bind(&user.role)
-            .bind(&user.avatar_url)
-            .bind(&user.locale)
-            .bind(&user.timezone)
-            .bind(user.created_at)
-            .bind(user.updated_at)
-            .fetch_one(&self.pool)
             .await
+            .and_then(|row| {
+                row.ok_or_else(|| DatabaseError::Query("Created user was not found".to_string()))
+            })
+            .and_then(|row| UserRow::from_db_row(&row))
             .map_err(|e| Error::database_with_source("Failed to create user", e))
         }
 
         pub async fn update_last_login(&self, user_id: Uuid) -> Result<()> {
-            sqlx::query("UPDATE users SET last_login_at = NOW(), updated_at = NOW() WHERE id = $1")
-                .bind(user_id)
-                .execute(&self.pool)
+            self.pool
+                .execute(
+                    "UPDATE users SET last_login_at = $2, updated_at = $2 WHERE id = $1",
+                    &[user_id.into(), Utc::now().into()],
+                )
                 .await
                 .map_err(|e| Error::database_with_source("Failed to update last login", e))?;
             Ok(())
         }
 
         pub async fn verify_email(&self, user_id: Uuid) -> Result<()> {
-            sqlx::query(
-                "UPDATE users SET email_verified_at = NOW(), status = 'active', updated_at = NOW() WHERE id = $1",
-            )
-            .bind(user_id)
-            .execute(&self.pool)
-            .await
-            .map_err(|e| Error::database_with_source("Failed to verify email", e))?;
+            self.pool
+                .execute(
+                    "UPDATE users SET email_verified_at = $2, status = 'active', updated_at = $2 WHERE id = $1",
+                    &[user_id.into(), Utc::now().into()],
+                )
+                .await
+                .map_err(|e| Error::database_with_source("Failed to verify email", e))?;
             Ok(())
         }
     }
@@ -237,26 +421,88 @@ pub mod posts {
         pub canonical_url: Option<String>,
         pub published_at: Option<DateTime<Utc>>,
         pub scheduled_at: Option<DateTime<Utc>>,
+        /// When the post goes back to draft
+        pub expires_at: Option<DateTime<Utc>>,
+        /// Start of the period the post is withheld in
+        pub embargo_starts_at: Option<DateTime<Utc>>,
+        pub embargo_ends_at: Option<DateTime<Utc>>,
+        /// IANA timezone the schedule was entered in
+        pub schedule_timezone: Option<String>,
         pub created_at: DateTime<Utc>,
         pub updated_at: DateTime<Utc>,
         pub deleted_at: Option<DateTime<Utc>>,
+        pub version: i64,
     }
 
     impl PostRow {
         /// Columns to select (excludes search_vector, casts enums to text)
-        pub const COLUMNS: &'static str = "id, site_id, post_type::text as post_type, author_id, title, slug, content, excerpt, status::text as status, visibility, password, parent_id, menu_order, template, featured_image_id, comment_status, comment_count, ping_status, meta_title, meta_description, canonical_url, published_at, scheduled_at, created_at, updated_at, deleted_at";
+        pub const COLUMNS: &'static str = "id, site_id, post_type::text as post_type, author_id, title, slug, content, excerpt, status::text as status, visibility, password, parent_id, menu_order, template, featured_image_id, comment_status, comment_count, ping_status, meta_title, meta_description, canonical_url, published_at, scheduled_at, expires_at, embargo_starts_at, embargo_ends_at, schedule_timezone, created_at, updated_at, deleted_at, version";
+
+        /// [`COLUMNS`](Self::COLUMNS) for a backend; the enum types only
+        /// exist on PostgreSQL
+        pub fn columns(kind: DatabaseKind) -> String {
+            match kind {
+                DatabaseKind::Postgres => Self::COLUMNS.to_string(),
+                _ => Self::COLUMNS.replace("::text", ""),
+            }
+        }
+    }
+
+    impl FromDbRow for PostRow {
+        fn from_db_row(row: &DbRow) -> std::result::Result<Self, DatabaseError> {
+            Ok(Self {
+                id: row.get("id")?,
+                site_id: row.get("site_id")?,
+                post_type: row.get("post_type")?,
+                author_id: row.get("author_id")?,
+                title: row.get("title")?,
+                slug: row.get("slug")?,
+                content: row.get("content")?,
+                excerpt: row.get("excerpt")?,
+                status: row.get("status")?,
+                visibility: row.get("visibility")?,
+                password: row.get("password")?,
+                parent_id: row.get("parent_id")?,
+                menu_order: row.get("menu_order")?,
+                template: row.get("template")?,
+                featured_image_id: row.get("featured_image_id")?,
+                comment_status: row.get("comment_status")?,
+                comment_count: row.get("comment_count")?,
+                ping_status: row.get("ping_status")?,
+                meta_title: row.get("meta_title")?,
+                meta_description: row.get("meta_description")?,
+                canonical_url: row.get("canonical_url")?,
+                published_at: row.get("published_at")?,
+                scheduled_at: row.get("scheduled_at")?,
+                expires_at: row.get("expires_at")?,
+                embargo_starts_at: row.get("embargo_starts_at")?,
+                embargo_ends_at: row.get("embargo_ends_at")?,
+                schedule_timezone: row.get("schedule_timezone")?,
+                created_at: row.get("created_at")?,
+                updated_at: row.get("updated_at")?,
+                deleted_at: row.get("deleted_at")?,
+                version: row.get("version")?,
+            })
+        }
     }
 
     pub struct PostRepository {
-        pool: PgPool,
+        pool: ProviderPool,
         site_id: Option<Uuid>,
     }
 
     impl PostRepository {
+        /// Repository for the site the current request serves
         pub fn new(pool: PgPool) -> Self {
+            Self::from_provider(ProviderPool::Postgres(pool))
+        }
+
+        /// Repository on whichever backend the provider connected to, for
+        /// the site the current request serves
+        pub fn from_provider(pool: ProviderPool) -> Self {
             Self {
                 pool,
-                site_id: None,
+                site_id: current_site(),
             }
         }
 
@@ -272,40 +518,64 @@ pub mod posts {
             }
         }
 
+        fn columns(&self) -> String {
+            PostRow::columns(self.pool.kind())
+        }
+
+        /// Cast to one of the PostgreSQL enum types; other backends store
+        /// them as plain strings
+        fn enum_cast(&self, type_name: &str) -> String {
+            match self.pool.kind() {
+                DatabaseKind::Postgres => format!("::{}", type_name),
+                _ => String::new(),
+            }
+        }
+
+        /// Condition matching the search term against title and content,
+        /// with the term bound as `$1`
+        fn search(&self, search: Option<&str>) -> (String, Vec<DbValue>) {
+            match search {
+                Some(term) => (
+                    format!(
+                        "(title {0} $1 OR content {0} $1)",
+                        QueryHelper::case_insensitive_like(self.pool.kind())
+                    ),
+                    vec![format!("%{}%", term).into()],
+                ),
+                None => ("1=1".to_string(), Vec::new()),
+            }
+        }
+
         pub async fn find_by_id(&self, id: Uuid) -> Result<Option<PostRow>> {
             let query = format!(
                 "SELECT {} FROM posts WHERE id = $1 AND {} AND deleted_at IS NULL",
-                PostRow::COLUMNS,
+                self.columns(),
                 self.site_condition()
             );
 
-            sqlx::query_as::<_, PostRow>(&query)
-                .bind(id)
-                .fetch_optional(&self.pool)
+            self.pool
+                .fetch_optional(&query, &[id.into()])
                 .await
+                .and_then(decode_optional)
                 .map_err(|e| Error::database_with_source("Failed to find post", e))
         }
 
         pub async fn find_by_slug(&self, slug: &str) -> Result<Option<PostRow>> {
             let query = format!(
                 "SELECT {} FROM posts WHERE slug = $1 AND {} AND deleted_at IS NULL",
-                PostRow::COLUMNS,
+                self.columns(),
                 self.site_condition()
             );
 
-            sqlx::query_as::<_, PostRow>(&query)
-                .bind(slug)
-                .fetch_optional(&self.pool)
+            self.pool
+                .fetch_optional(&query, &[slug.into()])
                 .await
+                .and_then(decode_optional)
                 .map_err(|e| Error::database_with_source("Failed to find post by slug", e))
         }
 
         pub async fn list(&self, params: &ListParams) -> Result<ListResult<PostRow>> {
-            let search_condition = params
-                .search
-                .as_ref()
-                .map(|s| QueryHelper::search_condition(s, &["title", "content"]))
-                .unwrap_or_else(|| "1=1".to_string());
+            let (search_condition, search_params) = self.search(params.search.as_deref());
 
             let count_query = format!(
                 "SELECT COUNT(*) as count FROM posts WHERE {} AND {} AND deleted_at IS NULL",
@@ -313,34 +583,34 @@ pub mod posts {
                 search_condition
             );
 
-            let total: (i64,) = sqlx::query_as(&count_query)
-                .fetch_one(&self.pool)
+            let total: i64 = self
+                .pool
+                .fetch_one(&count_query, &search_params)
                 .await
+                .and_then(|row| row.get("count"))
                 .map_err(|e| Error::database_with_source("Failed to count posts", e))?;
 
             let query = format!(
                 "SELECT {} FROM posts WHERE {} AND {} AND deleted_at IS NULL {} {}",
-                PostRow::COLUMNS,
+                self.columns(),
                 self.site_condition(),
                 search_condition,
                 QueryHelper::order_by(params.sort_by.as_deref(), params.sort_order),
                 QueryHelper::pagination(params)
             );
 
-            let posts = sqlx::query_as::<_, PostRow>(&query)
-                .fetch_all(&self.pool)
+            let posts = self
+                .pool
+                .fetch_all(&query, &search_params)
                 .await
+                .and_then(decode_all)
                 .map_err(|e| Error::database_with_source("Failed to list posts", e))?;
 
-            Ok(ListResult::new(posts, total.0 as u64, params))
+            Ok(ListResult::new(posts, total as u64, params))
         }
 
         pub async fn list_published(&self, params: &ListParams) -> Result<ListResult<PostRow>> {
-            let search_condition = params
-                .search
-                .as_ref()
-                .map(|s| QueryHelper::search_condition(s, &["title", "content"]))
-                .unwrap_or_else(|| "1=1".to_string());
+            let (search_condition, search_params) = self.search(params.search.as_deref());
 
             let count_query = format!(
                 "SELECT COUNT(*) as count FROM posts WHERE {} AND {} AND status = 'published' AND deleted_at IS NULL",
@@ -348,77 +618,96 @@ pub mod posts {
                 search_condition
             );
 
-            let total: (i64,) = sqlx::query_as(&count_query)
-                .fetch_one(&self.pool)
+            let total: i64 = self
+                .pool
+                .fetch_one(&count_query, &search_params)
                 .await
+                .and_then(|row| row.get("count"))
                 .map_err(|e| Error::database_with_source("Failed to count posts", e))?;
 
             let query = format!(
                 "SELECT {} FROM posts WHERE {} AND {} AND status = 'published' AND deleted_at IS NULL {} {}",
-                PostRow::COLUMNS,
+                self.columns(),
                 self.site_condition(),
                 search_condition,
                 QueryHelper::order_by(Some("published_at"), SortOrder::Desc),
                 QueryHelper::pagination(params)
             );
 
-            let posts = sqlx::query_as::<_, PostRow>(&query)
-                .fetch_all(&self.pool)
+            let posts = self
+                .pool
+                .fetch_all(&query, &search_params)
                 .await
+                .and_then(decode_all)
                 .map_err(|e| Error::database_with_source("Failed to list published posts", e))?;
 
-            Ok(ListResult::new(posts, total.0 as u64, params))
+            Ok(ListResult::new(posts, total as u64, params))
         }
 
         pub async fn create(&self, post: &PostRow) -> Result<PostRow> {
-            let query = format!(
+            let write = format!(
                 r#"
-                INSERT INTO posts (id, site_id, post_type, author_id, title, slug, content, excerpt, status, visibility, password, parent_id, menu_order, template, featured_image_id, comment_status, comment_count, ping_status, meta_title, meta_description, canonical_url, published_at, scheduled_at, created_at, updated_at)
-                VALUES ($1, $2, $3::post_type, $4, $5, $6, $7, $8, $9::post_status, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $22, $23, $24, $25)
-                RETURNING {}
+                INSERT INTO posts (id, site_id, post_type, author_id, title, slug, content, excerpt, status, visibility, password, parent_id, menu_order, template, featured_image_id, comment_status, comment_count, ping_status, meta_title, meta_description, canonical_url, published_at, scheduled_at, expires_at, embargo_starts_at, embargo_ends_at, schedule_timezone, created_at, updated_at)
+                VALUES ($1, $2, $3{}, $4, $5, $6, $7, $8, $9{}, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $22, $23, $24, $25, $26, $27, $28, $29)
                 "#,
-                PostRow::COLUMNS
+                self.enum_cast("post_type"),
+                self.enum_cast("post_status")
             );
-            sqlx::query_as::<_, PostRow>(&query)
-                .bind(post.id)
-                .bind(post.site_id)
-                .bind(&post.post_type)
-                .bind(post.author_id)
-                .bind(&post.title)
-                .bind(&post.slug)
-                .bind(&post.content)
-                .bind(&post.excerpt)
-                .bind(&post.status)
-                .bind(&post.visibility)
-                .bind(&post.password)
-                .bind(post.parent_id)
-                .bind(post.menu_order)
-                .bind(&post.template)
-                .bind(post.featured_image_id)
-                .bind(&post.comment_status)
-                .bind(post.comment_count)
-                .bind(&post.ping_status)
-                .bind(&post.meta_title)
-                .bind(&post.meta_description)
-                .bind(&post.canonical_url)
-                .bind(post.published_at)
-                .bind(post.scheduled_at)
-                .bind(post.created_at)
-                .bind(post.updated_at)
-                .fetch_one(&self.pool)
+            let params = [
+                post.id.into(),
+                post.site_id.into(),
+                post.post_type.as_str().into(),
+                post.author_id.into(),
+                post.title.as_str().into(),
+                post.slug.as_str().into(),
+                post.content.clone().into(),
+                post.excerpt.clone().into(),
+                post.status.as_str().into(),
+                post.visibility.as_str().into(),
+                post.password.clone().into(),
+                post.parent_id.into(),
+                post.menu_order.into(),
+                post.template.clone().into(),
+                post.featured_image_id.into(),
+                post.comment_status.as_str().into(),
+                post.comment_count.into(),
+                post.ping_status.as_str().into(),
+                post.meta_title.clone().into(),
+                post.meta_description.clone().into(),
+                post.canonical_url.clone().into(),
+                post.published_at.into(),
+                post.scheduled_at.into(),
+                post.expires_at.into(),
+                post.embargo_starts_at.into(),
+                post.embargo_ends_at.into(),
+                post.schedule_timezone.clone().into(),
+                post.created_at.into(),
+                post.updated_at.into(),
+            ];
+            let columns = self.columns();
+            let select = format!("SELECT {} FROM posts WHERE id = $1", columns);
+
+            write_returning(&self.pool, &write, &columns, &select, &params)
                 .await
+                .and_then(|row| {
+                    row.ok_or_else(|| {
+                        DatabaseError::Query("Created post was not found".to_string())
+                    })
+                })
+                .and_then(|row| PostRow::from_db_row(&row))
                 .map_err(|e| Error::database_with_source("Failed to create post", e))
         }
 
+        /// Update a post, requiring `post.version` to match the stored version
         pub async fn update(&self, post: &PostRow) -> Result<PostRow> {
-            let query = format!(
+            let write = format!(
                 r#"
                 UPDATE posts SET
                     title = $2,
                     slug = $3,
                     content = $4,
                     excerpt = $5,
-                    status = $6::post_status,
+                    status = $6{},
                     visibility = $7,
                     password = $8,
                     parent_id = $9,
@@ -432,50 +721,94 @@ pub mod posts {
                     canonical_url = $17,
                     published_at = $18,
                     scheduled_at = $19,
-                    updated_at = NOW()
-                WHERE id = $1
-                RETURNING {}
+                    expires_at = $20,
+                    embargo_starts_at = $21,
+                    embargo_ends_at = $22,
+                    schedule_timezone = $23,
+                    updated_at = $25,
+                    version = version + 1
+                WHERE id = $1 AND {} AND {}
                 "#,
-                PostRow::COLUMNS
+                self.enum_cast("post_status"),
+                self.site_condition(),
+                Versioning::provider_guard(24)
             );
-            sqlx::query_as::<_, PostRow>(&query)
-                .bind(post.id)
-                .bind(&post.title)
-                .bind(&post.slug)
-                .bind(&post.content)
-                .bind(&post.excerpt)
-                .bind(&post.status)
-                .bind(&post.visibility)
-                .bind(&post.password)
-                .bind(post.parent_id)
-                .bind(post.menu_order)
-                .bind(&post.template)
-                .bind(post.featured_image_id)
-                .bind(&post.comment_status)
-                .bind(&post.ping_status)
-                .bind(&post.meta_title)
-                .bind(&post.meta_description)
-                .bind(&post.canonical_url)
-                .bind(post.published_at)
-                .bind(post.scheduled_at)
-                .fetch_one(&self.pool)
+            let params = [
+                post.id.into(),
+                post.title.as_str().into(),
+                post.slug.as_str().into(),
+                post.content.clone().into(),
+                post.excerpt.clone().into(),
+                post.status.as_str().into(),
+                post.visibility.as_str().into(),
+                post.password.clone().into(),
+                post.parent_id.into(),
+                post.menu_order.into(),
+                post.template.clone().into(),
+                post.featured_image_id.into(),
+                post.comment_status.as_str().into(),
+                post.ping_status.as_str().into(),
+                post.meta_title.clone().into(),
+                post.meta_description.clone().into(),
+                post.canonical_url.clone().into(),
+                post.published_at.into(),
+                post.scheduled_at.into(),
+                post.expires_at.into(),
+                post.embargo_starts_at.into(),
+                post.embargo_ends_at.into(),
+                post.schedule_timezone.clone().into(),
+                post.version.into(),
+                Utc::now().into(),
+            ];
+            let columns = self.columns();
+            let select = format!(
+                "SELECT {} FROM posts WHERE id = $1 AND {}",
+                columns,
+                self.site_condition()
+            );
+
+            let updated = write_returning(&self.pool, &write, &columns, &select, &params)
                 .await
-                .map_err(|e| Error::database_with_source("Failed to update post", e))
+                .and_then(decode_optional)
+                .map_err(|e| Error::database_with_source("Failed to update post", e))?;
+
+            match updated {
+                Some(row) => Ok(row),
+                None => Err(Versioning::resolve_provider_conflict(
+                    &self.pool,
+                    "Post",
+                    "posts",
+                    "id",
+                    post.id,
+                    &format!("{} AND deleted_at IS NULL", self.site_condition()),
+                )
+                .await),
+            }
         }
 
         pub async fn soft_delete(&self, id: Uuid) -> Result<()> {
-            sqlx::query("UPDATE posts SET deleted_at = NOW(), updated_at = NOW() WHERE id = $1")
-                .bind(id)
-                .execute(&self.pool)
+            self.pool
+                .execute(
+                    &format!(
+                        "UPDATE posts SET deleted_at = $2, updated_at = $2 WHERE id = $1 AND {}",
+                        self.site_condition()
+                    ),
+                    &[id.into(), Utc::now().into()],
+                )
                 .await
                 .map_err(|e| Error::database_with_source("Failed to delete post", e))?;
             Ok(())
         }
 
         pub async fn restore(&self, id: Uuid) -> Result<()> {
-            sqlx::query("UPDATE posts SET deleted_at = NULL, updated_at = NOW() WHERE id = $1")
-                .bind(id)
-                .execute(&self.pool)
+            self.pool
+                .execute(
+                    &format!(
+                        "UPDATE posts SET deleted_at = NULL, updated_at = $2 WHERE id = $1 AND {}",
+                        self.site_condition()
+                    ),
+                    &[id.into(), Utc::now().into()],
+                )
                 .await
                 .map_err(|e| Error::database_with_source("Failed to restore post", e))?;
             Ok(())
@@ -504,6 +837,28 @@ pub mod options {
         pub description: Option<String>,
         pub created_at: DateTime<Utc>,
         pub updated_at: DateTime<Utc>,
+        pub version: i64,
+    }
+
+    impl FromDbRow for OptionRow {
+        fn from_db_row(row: &DbRow) -> std::result::Result<Self, DatabaseError> {
+            Ok(Self {
+                id: row.get("id")?,
+                site_id: row.get("site_id")?,
+                option_name: row.get("option_name")?,
+                option_value: row.get("option_value")?,
+                option_group: row.get("option_group")?,
+                autoload: row.get("autoload")?,
+                is_system: row.get("is_system")?,
+                value_type: row.get("value_type")?,
+                validation: row.get("validation")?,
+                display_name: row.get("display_name")?,
+                description: row.get("description")?,
+                created_at: row.get("created_at")?,
+                updated_at: row.get("updated_at")?,
+                version: row.get("version")?,
+            })
+        }
     }
 
     /// Grouped settings response
@@ -514,15 +869,22 @@ pub mod options {
     }
 
     pub struct OptionsRepository {
-        pool: PgPool,
+        pool: ProviderPool,
         site_id: Option<Uuid>,
     }
 
     impl OptionsRepository {
+        /// Repository for the site the current request serves
         pub fn new(pool: PgPool) -> Self {
+            Self::from_provider(ProviderPool::Postgres(pool))
+        }
+
+        /// Repository on whichever backend the provider connected to, for
+        /// the site the current request serves
+        pub fn from_provider(pool: ProviderPool) -> Self {
             Self {
                 pool,
-                site_id: None,
+                site_id: current_site(),
             }
         }
 
@@ -538,6 +900,27 @@ pub mod options {
             }
         }
 
+        /// Versioned update of one option, bound as value `$1`, name `$2`,
+        /// expected version `$3` and update time `$4`
+        fn versioned_update(&self) -> String {
+            format!(
+                r#"
+                UPDATE options
+                SET option_value = $1, updated_at = $4, version = version + 1
+                WHERE option_name = $2 AND {} AND {}
+                "#,
+                self.site_condition(),
+                Versioning::provider_guard(3)
+            )
+        }
+
+        fn select_by_name(&self) -> String {
+            format!(
+                "SELECT * FROM options WHERE option_name = $2 AND {}",
+                self.site_condition()
+            )
+        }
+
         /// Get a single option value by name
         pub async fn get(&self, name: &str) -> Result<Option<serde_json::Value>> {
             let query = format!(
@@ -545,13 +928,17 @@ pub mod options {
                 self.site_condition()
             );
 
-            let result: Option<(Option<serde_json::Value>,)> = sqlx::query_as(&query)
-                .bind(name)
-                .fetch_optional(&self.pool)
+            let result = self
+                .pool
+                .fetch_optional(&query, &[name.into()])
                 .await
+                .and_then(|row| {
+                    row.map(|row| row.get::<Option<serde_json::Value>>("option_value"))
+                        .transpose()
+                })
                 .map_err(|e| Error::database_with_source("Failed to get option", e))?;
 
-            Ok(result.and_then(|(v,)| v))
+            Ok(result.flatten())
         }
 
         /// Get full option row by name
@@ -561,174 +948,1180 @@ pub mod options {
                 self.site_condition()
             );
 
-            sqlx::query_as::<_, OptionRow>(&query)
-                .bind(name)
-                .fetch_optional(&self.pool)
+            self.pool
+                .fetch_optional(&query, &[name.into()])
                 .await
+                .and_then(decode_optional)
                 .map_err(|e| Error::database_with_source("Failed to get option", e))
         }
 
         /// Set an option value (upsert)
         pub async fn set(&self, name: &str, value: serde_json::Value) -> Result<()> {
-            let id = Uuid::now_v7();
+            let query = match self.pool.kind() {
+                DatabaseKind::MySql => {
+                    r#"
+                    INSERT INTO options (id, site_id, option_name, option_value, created_at, updated_at)
+                    VALUES ($1, $2, $3, $4, $5, $5)
+                    ON DUPLICATE KEY UPDATE
+                        option_value = VALUES(option_value),
+                        updated_at = VALUES(updated_at),
+                        version = version + 1
+                    "#
+                }
+                DatabaseKind::Postgres | DatabaseKind::Sqlite => {
+                    r#"
+                    INSERT INTO options (id, site_id, option_name, option_value, created_at, updated_at)
+                    VALUES ($1, $2, $3, $4, $5, $5)
+                    ON CONFLICT (option_name, site_id) DO UPDATE SET
+                        option_value = EXCLUDED.option_value,
+                        updated_at = EXCLUDED.updated_at,
+                        version = options.version + 1
+                    "#
+                }
+            };
+
+            self.pool
+                .execute(
+                    query,
+                    &[
+                        Uuid::now_v7().into(),
+                        self.site_id.into(),
+                        name.into(),
+                        value.into(),
+                        Utc::now().into(),
+                    ],
+                )
+                .await
+                .map_err(|e| Error::database_with_source("Failed to set option", e))?;
+
+            Ok(())
+        }
+
+        /// Update an existing option, requiring the stored version to match
+        /// `expected_version` when given. Returns the updated row.
+        pub async fn set_versioned(
+            &self,
+            name: &str,
+            value: serde_json::Value,
+            expected_version: Option<i64>,
+        ) -> Result<OptionRow> {
+            let params = [
+                value.into(),
+                name.into(),
+                expected_version.into(),
+                Utc::now().into(),
+            ];
+
+            let updated = write_returning(
+                &self.pool,
+                &self.versioned_update(),
+                "*",
+                &self.select_by_name(),
+                &params,
+            )
+            .await
+            .and_then(decode_optional)
+            .map_err(|e| Error::database_with_source("Failed to set option", e))?;
+
+            match updated {
+                Some(row) => Ok(row),
+                None => Err(Versioning::resolve_provider_conflict(
+                    &self.pool,
+                    "Setting",
+                    "options",
+                    "option_name",
+                    name,
+                    &self.site_condition(),
+                )
+                .await),
+            }
+        }
+
+        /// Delete an option (only non-system options)
+        pub async fn delete(&self, name: &str) -> Result<bool> {
+            let query = format!(
+                "DELETE FROM options WHERE option_name = $1 AND {} AND is_system = false",
+                self.site_condition()
+            );
+
+            let deleted = self
+                .pool
+                .execute(&query, &[name.into()])
+                .await
+                .map_err(|e| Error::database_with_source("Failed to delete option", e))?;
+
+            Ok(deleted > 0)
+        }
+
+        /// Get all options that should be autoloaded
+        pub async fn get_autoload(&self) -> Result<Vec<OptionRow>> {
+            let query = format!(
+                "SELECT * FROM options WHERE autoload = true AND ({} OR site_id IS NULL) ORDER BY option_group, option_name",
+                self.site_condition()
+            );
+
+            self.pool
+                .fetch_all(&query, &[])
+                .await
+                .and_then(decode_all)
+                .map_err(|e| Error::database_with_source("Failed to get autoload options", e))
+        }
+
+        /// Get all options
+        pub async fn get_all(&self) -> Result<Vec<OptionRow>> {
+            let query = format!(
+                "SELECT * FROM options WHERE ({} OR site_id IS NULL) ORDER BY option_group, option_name",
+                self.site_condition()
+            );
+
+            self.pool
+                .fetch_all(&query, &[])
+                .await
+                .and_then(decode_all)
+                .map_err(|e| Error::database_with_source("Failed to get all options", e))
+        }
+
+        /// Get options by group
+        pub async fn get_by_group(&self, group: &str) -> Result<Vec<OptionRow>> {
+            let query = format!(
+                "SELECT * FROM options WHERE option_group = $1 AND ({} OR site_id IS NULL) ORDER BY option_name",
+                self.site_condition()
+            );
+
+            self.pool
+                .fetch_all(&query, &[group.into()])
+                .await
+                .and_then(decode_all)
+                .map_err(|e| Error::database_with_source("Failed to get options by group", e))
+        }
+
+        /// Get all options grouped by their option_group
+        pub async fn get_all_grouped(&self) -> Result<Vec<SettingsGroup>> {
+            let options = self.get_all().await?;
+
+            let mut groups: std::collections::HashMap<String, Vec<OptionRow>> =
+                std::collections::HashMap::new();
+            for option in options {
+                groups
+                    .entry(option.option_group.clone())
+                    .or_default()
+                    .push(option);
+            }
+
+            let mut result: Vec<SettingsGroup> = groups
+                .into_iter()
+                .map(|(group, settings)| SettingsGroup { group, settings })
+                .collect();
+
+            // Sort groups in a logical order
+            let group_order = [
+                "general",
+                "reading",
+                "discussion",
+                "media",
+                "permalinks",
+                "privacy",
+                "security",
+            ];
+            result.sort_by(|a, b| {
+                let a_idx = group_order
+                    .iter()
+                    .position(|&g| g == a.group)
+                    .unwrap_or(999);
+                let b_idx = group_order
+                    .iter()
+                    .position(|&g| g == b.group)
+                    .unwrap_or(999);
+                a_idx.cmp(&b_idx)
+            });
+
+            Ok(result)
+        }
+
+        /// Batch update multiple options
+        pub async fn batch_update(&self, updates: Vec<(String, serde_json::Value)>) -> Result<u64> {
+            let updates = updates
+                .into_iter()
+                .map(|(name, value)| (name, value, None))
+                .collect();
+            Ok(self.batch_update_versioned(updates).await?.len() as u64)
+        }
+
+        /// Update several existing options in one transaction.
+        ///
+        /// Each entry may carry the version the caller read; if any guarded
+        /// option is stale or missing the whole batch is rolled back with a
+        /// version conflict (or not found) error. Unguarded entries that do
+        /// not exist are skipped. Returns the updated rows.
+        pub async fn batch_update_versioned(
+            &self,
+            updates: Vec<(String, serde_json::Value, Option<i64>)>,
+        ) -> Result<Vec<OptionRow>> {
+            let update = self.versioned_update();
+            let select = self.select_by_name();
+            let returning = format!("{} RETURNING *", update);
+            let updated_at = Utc::now();
+
+            let mut tx = self
+                .pool
+                .begin()
+                .await
+                .map_err(|e| Error::database_with_source("Failed to begin transaction", e))?;
+            let mut rows = Vec::with_capacity(updates.len());
+
+            for (name, value, expected_version) in updates {
+                let params = [
+                    value.into(),
+                    name.as_str().into(),
+                    expected_version.into(),
+                    updated_at.into(),
+                ];
+                // MySQL has no RETURNING, so the row is read back there
+                let updated = if tx.kind() == DatabaseKind::MySql {
+                    match tx.execute(&update, &params).await {
+                        Ok(0) => Ok(None),
+                        Ok(_) => tx.fetch_optional(&select, &params).await,
+                        Err(e) => Err(e),
+                    }
+                } else {
+                    tx.fetch_optional(&returning, &params).await
+                }
+                .and_then(decode_optional)
+                .map_err(|e| Error::database_with_source("Failed to batch update options", e))?;
+
+                match updated {
+                    Some(row) => rows.push(row),
+                    None if expected_version.is_none() => {}
+                    None => {
+                        tx.rollback().await.map_err(|e| {
+                            Error::database_with_source("Failed to roll back transaction", e)
+                        })?;
+                        return Err(Versioning::resolve_provider_conflict(
+                            &self.pool,
+                            "Setting",
+                            "options",
+                            "option_name",
+                            name,
+                            &self.site_condition(),
+                        )
+                        .await);
+                    }
+                }
+            }
+
+            tx.commit()
+                .await
+                .map_err(|e| Error::database_with_source("Failed to commit options", e))?;
+            Ok(rows)
+        }
+
+        /// Get available option groups
+        pub async fn get_groups(&self) -> Result<Vec<String>> {
+            let query = format!(
+                "SELECT DISTINCT option_group FROM options WHERE ({} OR site_id IS NULL) ORDER BY option_group",
+                self.site_condition()
+            );
+
+            self.pool
+                .fetch_all(&query, &[])
+                .await
+                .and_then(|rows| rows.iter().map(|row| row.get("option_group")).collect())
+                .map_err(|e| Error::database_with_source("Failed to get option groups", e))
+        }
+    }
+}
+
+/// Menus repository
+pub mod menus {
+    use super::*;
+    use chrono::{DateTime, Utc};
+    use sqlx::types::Json;
+
+    /// Menu row
+    #[derive(Debug, Clone, sqlx::FromRow, serde::Serialize, serde::Deserialize)]
+    pub struct MenuRow {
+        pub id: Uuid,
+        pub name: String,
+        pub slug: String,
+        pub description: Option<String>,
+        /// Location filled when the active theme has no assignment for it
+        pub location: Option<String>,
+        pub version: i64,
+        pub created_at: DateTime<Utc>,
+        pub updated_at: DateTime<Utc>,
+    }
+
+    const MENU_COLUMNS: &str =
+        "id, name, slug, description, location, version, created_at, updated_at";
+
+    /// Menu item row. Parents always sort before their children.
+    #[derive(Debug, Clone, sqlx::FromRow, serde::Serialize, serde::Deserialize)]
+    pub struct MenuItemRow {
+        pub id: Uuid,
+        pub menu_id: Uuid,
+        pub parent_id: Option<Uuid>,
+        /// Empty for post and term links that follow their object's title
+        pub title: String,
+        pub url: Option<String>,
+        pub target: Option<String>,
+        /// `custom`, `post` or `term`
+        pub object_type: String,
+        pub object_id: Option<Uuid>,
+        pub menu_order: i32,
+        pub css_classes: Option<String>,
+        /// `everyone`, `logged_in`, `logged_out` or `roles`
+        pub visibility: String,
+        pub roles: Json<Vec<String>>,
+    }
+
+    const ITEM_COLUMNS: &str = "id, menu_id, parent_id, title, url, target, object_type, \
+         object_id, menu_order, css_classes, visibility, roles";
+
+    /// Menu fields to save
+    #[derive(Debug, Clone)]
+    pub struct SaveMenu {
+        pub name: String,
+        pub slug: String,
+        pub description: Option<String>,
+        pub location: Option<String>,
+    }
+
+    pub struct MenusRepository {
+        pool: PgPool,
+        site_id: Option<Uuid>,
+    }
+
+    impl MenusRepository {
+        /// Repository for the site the current request serves
+        pub fn new(pool: PgPool) -> Self {
+            Self {
+                pool,
+                site_id: current_site(),
+            }
+        }
+
+        pub fn with_site(mut self, site_id: Uuid) -> Self {
+            self.site_id = Some(site_id);
+            self
+        }
+
+        fn site_condition(&self) -> String {
+            match self.site_id {
+                Some(id) => format!("site_id = '{}'", id),
+                None => "site_id IS NULL".to_string(),
+            }
+        }
+
+        fn save_error(e: sqlx::Error) -> Error {
+            match &e {
+                sqlx::Error::Database(db) if db.code().as_deref() == Some("23505") => {
+                    Error::Duplicate {
+                        entity_type: "Menu".to_string(),
+                        field: "slug".to_string(),
+                    }
+                }
+                _ => Error::database_with_source("Failed to save menu", e),
+            }
+        }
+
+        /// All menus by name
+        pub async fn list(&self) -> Result<Vec<MenuRow>> {
+            sqlx::query_as(&format!(
+                "SELECT {} FROM menus WHERE {} ORDER BY name, id",
+                MENU_COLUMNS,
+                self.site_condition()
+            ))
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to list menus", e))
+        }
+
+        pub async fn find(&self, id: Uuid) -> Result<Option<MenuRow>> {
+            sqlx::query_as(&format!(
+                "SELECT {} FROM menus WHERE id = $1 AND {}",
+                MENU_COLUMNS,
+                self.site_condition()
+            ))
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to get menu", e))
+        }
+
+        pub async fn create(&self, menu: &SaveMenu) -> Result<MenuRow> {
+            sqlx::query_as(&format!(
+                "INSERT INTO menus (id, site_id, name, slug, description, location) \
+                 VALUES ($1, $2, $3, $4, $5, $6) RETURNING {}",
+                MENU_COLUMNS
+            ))
+            .bind(Uuid::now_v7())
+            .bind(self.site_id)
+            .bind(&menu.name)
+            .bind(&menu.slug)
+            .bind(&menu.description)
+            .bind(&menu.location)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(Self::save_error)
+        }
+
+        /// Save a menu, requiring the stored version to match
+        /// `expected_version` when given
+        pub async fn update(
+            &self,
+            id: Uuid,
+            menu: &SaveMenu,
+            expected_version: Option<i64>,
+        ) -> Result<MenuRow> {
+            let query = format!(
+                "UPDATE menus SET name = $1, slug = $2, description = $3, location = $4, \
+                 updated_at = NOW(), version = version + 1 \
+                 WHERE id = $5 AND {} AND {} RETURNING {}",
+                self.site_condition(),
+                Versioning::guard(6),
+                MENU_COLUMNS
+            );
+
+            let updated: Option<MenuRow> = sqlx::query_as(&query)
+                .bind(&menu.name)
+                .bind(&menu.slug)
+                .bind(&menu.description)
+                .bind(&menu.location)
+                .bind(id)
+                .bind(expected_version)
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(Self::save_error)?;
+
+            match updated {
+                Some(row) => Ok(row),
+                None => Err(Versioning::resolve_conflict(
+                    &self.pool,
+                    "Menu",
+                    "menus",
+                    "id",
+                    id,
+                    &self.site_condition(),
+                )
+                .await),
+            }
+        }
+
+        /// Delete a menu with its items and location assignments
+        pub async fn delete(&self, id: Uuid) -> Result<bool> {
+            let result = sqlx::query(&format!(
+                "DELETE FROM menus WHERE id = $1 AND {}",
+                self.site_condition()
+            ))
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to delete menu", e))?;
+            Ok(result.rows_affected() > 0)
+        }
+
+        /// Items of some menus, in order
+        pub async fn items(&self, menu_ids: &[Uuid]) -> Result<Vec<MenuItemRow>> {
+            sqlx::query_as(&format!(
+                "SELECT {} FROM menu_items WHERE menu_id = ANY($1) \
+                 AND menu_id IN (SELECT id FROM menus WHERE {}) \
+                 ORDER BY menu_id, menu_order, created_at",
+                ITEM_COLUMNS,
+                self.site_condition()
+            ))
+            .bind(menu_ids)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to load menu items", e))
+        }
+
+        /// Replace all items of a menu, bumping its version. Items must be
+        /// given parents first. Returns the new version.
+        pub async fn replace_items(
+            &self,
+            menu_id: Uuid,
+            items: &[MenuItemRow],
+            expected_version: Option<i64>,
+        ) -> Result<i64> {
+            let mut tx = self
+                .pool
+                .begin()
+                .await
+                .map_err(|e| Error::database_with_source("Failed to begin transaction", e))?;
+
+            let updated: Option<(i64,)> = sqlx::query_as(&format!(
+                "UPDATE menus SET updated_at = NOW(), version = version + 1 \
+                 WHERE id = $1 AND {} AND {} RETURNING version",
+                self.site_condition(),
+                Versioning::guard(2)
+            ))
+            .bind(menu_id)
+            .bind(expected_version)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to update menu", e))?;
+
+            let Some((version,)) = updated else {
+                drop(tx);
+                return Err(Versioning::resolve_conflict(
+                    &self.pool,
+                    "Menu",
+                    "menus",
+                    "id",
+                    menu_id,
+                    &self.site_condition(),
+                )
+                .await);
+            };
+
+            sqlx::query("DELETE FROM menu_items WHERE menu_id = $1")
+                .bind(menu_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| Error::database_with_source("Failed to clear menu items", e))?;
+
+            for item in items {
+                sqlx::query(
+                    r#"
+                    INSERT INTO menu_items (
+                        id, menu_id, parent_id, title, url, target, object_type,
+                        object_id, menu_order, css_classes, visibility, roles
+                    )
+                    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+                    "#,
+                )
+                .bind(item.id)
+                .bind(menu_id)
+                .bind(item.parent_id)
+                .bind(&item.title)
+                .bind(&item.url)
+                .bind(&item.target)
+                .bind(&item.object_type)
+                .bind(item.object_id)
+                .bind(item.menu_order)
+                .bind(&item.css_classes)
+                .bind(&item.visibility)
+                .bind(&item.roles)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| Error::database_with_source("Failed to insert menu item", e))?;
+            }
+
+            tx.commit()
+                .await
+                .map_err(|e| Error::database_with_source("Failed to save menu items", e))?;
+            Ok(version)
+        }
+    }
+}
+
+/// Widget areas and the widgets placed into them
+pub mod widgets {
+    use super::*;
+    use chrono::{DateTime, Utc};
+
+    /// Widget area a theme declares
+    #[derive(Debug, Clone, PartialEq, sqlx::FromRow, serde::Serialize, serde::Deserialize)]
+    pub struct WidgetAreaRow {
+        pub id: Uuid,
+        pub theme: String,
+        pub slug: String,
+        pub name: String,
+        pub description: Option<String>,
+        pub before_widget: Option<String>,
+        pub after_widget: Option<String>,
+        pub before_title: Option<String>,
+        pub after_title: Option<String>,
+        pub position: i32,
+    }
+
+    const AREA_COLUMNS: &str = "id, theme, slug, name, description, before_widget, \
+         after_widget, before_title, after_title, position";
+
+    /// Widget area as declared in a theme manifest
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct RegisterArea {
+        pub slug: String,
+        pub name: String,
+        pub description: Option<String>,
+        pub before_widget: Option<String>,
+        pub after_widget: Option<String>,
+        pub before_title: Option<String>,
+        pub after_title: Option<String>,
+    }
+
+    /// Widget placed into an area
+    #[derive(Debug, Clone, sqlx::FromRow, serde::Serialize, serde::Deserialize)]
+    pub struct WidgetRow {
+        pub id: Uuid,
+        pub area_id: Option<Uuid>,
+        /// Block name, or the type of a classic widget
+        pub widget_type: String,
+        pub title: Option<String>,
+        pub content: Option<String>,
+        pub settings: serde_json::Value,
+        pub widget_order: i32,
+        pub is_active: bool,
+        pub created_at: DateTime<Utc>,
+        pub updated_at: DateTime<Utc>,
+    }
+
+    const WIDGET_COLUMNS: &str = "id, area_id, widget_type, title, content, \
+         COALESCE(settings, '{}'::jsonb) AS settings, widget_order, is_active, created_at, updated_at";
+
+    /// Widgets of areas of the repository's site; widgets have no site of their own
+    const SITE_WIDGETS: &str =
+        "area_id IN (SELECT id FROM widget_areas WHERE site_id IS NOT DISTINCT FROM $2)";
+
+    /// Widget fields to save
+    #[derive(Debug, Clone)]
+    pub struct SaveWidget {
+        pub area_id: Uuid,
+        pub widget_type: String,
+        pub title: Option<String>,
+        pub content: Option<String>,
+        pub settings: serde_json::Value,
+        pub is_active: bool,
+    }
+
+    pub struct WidgetsRepository {
+        pool: PgPool,
+        site_id: Option<Uuid>,
+    }
+
+    impl WidgetsRepository {
+        pub fn new(pool: PgPool, site_id: Option<Uuid>) -> Self {
+            Self { pool, site_id }
+        }
+
+        /// Areas a theme currently declares, in manifest order
+        pub async fn areas(&self, theme: &str) -> Result<Vec<WidgetAreaRow>> {
+            sqlx::query_as(&format!(
+                "SELECT {} FROM widget_areas \
+                 WHERE theme = $1 AND site_id IS NOT DISTINCT FROM $2 AND deleted_at IS NULL \
+                 ORDER BY position, slug",
+                AREA_COLUMNS
+            ))
+            .bind(theme)
+            .bind(self.site_id)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to load widget areas", e))
+        }
+
+        pub async fn find_area(&self, id: Uuid) -> Result<Option<WidgetAreaRow>> {
+            sqlx::query_as(&format!(
+                "SELECT {} FROM widget_areas \
+                 WHERE id = $1 AND site_id IS NOT DISTINCT FROM $2 AND deleted_at IS NULL",
+                AREA_COLUMNS
+            ))
+            .bind(id)
+            .bind(self.site_id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to get widget area", e))
+        }
+
+        /// Make `areas` the areas of a theme. Areas the theme no longer
+        /// declares are retired with their widgets, which come back if the
+        /// area does. Widgets placed before areas were registered move into
+        /// the area named by their sidebar.
+        pub async fn register_areas(&self, theme: &str, areas: &[RegisterArea]) -> Result<()> {
+            let mut tx = self
+                .pool
+                .begin()
+                .await
+                .map_err(|e| Error::database_with_source("Failed to begin transaction", e))?;
+
+            for (position, area) in areas.iter().enumerate() {
+                sqlx::query(
+                    r#"
+                    INSERT INTO widget_areas (
+                        id, site_id, theme, slug, name, description, before_widget,
+                        after_widget, before_title, after_title, position
+                    )
+                    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+                    ON CONFLICT (site_id, theme, slug) DO UPDATE SET
+                        name = EXCLUDED.name,
+                        description = EXCLUDED.description,
+                        before_widget = EXCLUDED.before_widget,
+                        after_widget = EXCLUDED.after_widget,
+                        before_title = EXCLUDED.before_title,
+                        after_title = EXCLUDED.after_title,
+                        position = EXCLUDED.position,
+                        deleted_at = NULL,
+                        updated_at = NOW()
+                    "#,
+                )
+                .bind(Uuid::now_v7())
+                .bind(self.site_id)
+                .bind(theme)
+                .bind(&area.slug)
+                .bind(&area.name)
+                .bind(&area.description)
+                .bind(&area.before_widget)
+                .bind(&area.after_widget)
+                .bind(&area.before_title)
+                .bind(&area.after_title)
+                .bind(position as i32)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| Error::database_with_source("Failed to register widget area", e))?;
+            }
+
+            let slugs: Vec<&str> = areas.iter().map(|a| a.slug.as_str()).collect();
+            sqlx::query(
+                "UPDATE widget_areas SET deleted_at = NOW(), updated_at = NOW() \
+                 WHERE theme = $1 AND site_id IS NOT DISTINCT FROM $2 \
+                 AND deleted_at IS NULL AND slug <> ALL($3)",
+            )
+            .bind(theme)
+            .bind(self.site_id)
+            .bind(&slugs)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to retire widget areas", e))?;
+
+            sqlx::query(
+                r#"
+                UPDATE widgets w
+                SET area_id = a.id, sidebar = NULL, updated_at = NOW()
+                FROM widget_areas a
+                WHERE w.area_id IS NULL AND w.sidebar = a.slug
+                  AND a.theme = $1 AND a.site_id IS NOT DISTINCT FROM $2
+                  AND a.deleted_at IS NULL
+                "#,
+            )
+            .bind(theme)
+            .bind(self.site_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to adopt widgets", e))?;
+
+            tx.commit()
+                .await
+                .map_err(|e| Error::database_with_source("Failed to register widget areas", e))
+        }
+
+        /// Widgets of some areas, in order
+        pub async fn widgets(&self, area_ids: &[Uuid]) -> Result<Vec<WidgetRow>> {
+            sqlx::query_as(&format!(
+                "SELECT {} FROM widgets WHERE area_id = ANY($1) AND deleted_at IS NULL \
+                 ORDER BY area_id, widget_order, created_at",
+                WIDGET_COLUMNS
+            ))
+            .bind(area_ids)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to load widgets", e))
+        }
+
+        pub async fn find_widget(&self, id: Uuid) -> Result<Option<WidgetRow>> {
+            sqlx::query_as(&format!(
+                "SELECT {} FROM widgets WHERE id = $1 AND deleted_at IS NULL AND {}",
+                WIDGET_COLUMNS, SITE_WIDGETS
+            ))
+            .bind(id)
+            .bind(self.site_id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to get widget", e))
+        }
+
+        /// Add a widget at the end of its area
+        pub async fn create_widget(&self, widget: &SaveWidget) -> Result<WidgetRow> {
+            sqlx::query_as(&format!(
+                r#"
+                INSERT INTO widgets (
+                    id, area_id, widget_type, title, content, settings, is_active, widget_order
+                )
+                VALUES ($1, $2, $3, $4, $5, $6, $7, (
+                    SELECT COALESCE(MAX(widget_order) + 1, 0) FROM widgets
+                    WHERE area_id = $2 AND deleted_at IS NULL
+                ))
+                RETURNING {}
+                "#,
+                WIDGET_COLUMNS
+            ))
+            .bind(Uuid::now_v7())
+            .bind(widget.area_id)
+            .bind(&widget.widget_type)
+            .bind(&widget.title)
+            .bind(&widget.content)
+            .bind(&widget.settings)
+            .bind(widget.is_active)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to create widget", e))
+        }
+
+        /// Save a widget; moving it to another area puts it at the end
+        pub async fn update_widget(
+            &self,
+            id: Uuid,
+            widget: &SaveWidget,
+        ) -> Result<Option<WidgetRow>> {
+            sqlx::query_as(&format!(
+                r#"
+                UPDATE widgets SET
+                    widget_order = CASE WHEN area_id IS NOT DISTINCT FROM $2 THEN widget_order ELSE (
+                        SELECT COALESCE(MAX(widget_order) + 1, 0) FROM widgets
+                        WHERE area_id = $2 AND deleted_at IS NULL
+                    ) END,
+                    area_id = $2, widget_type = $3, title = $4, content = $5,
+                    settings = $6, is_active = $7, updated_at = NOW()
+                WHERE id = $1 AND deleted_at IS NULL
+                  AND area_id IN (SELECT id FROM widget_areas WHERE site_id IS NOT DISTINCT FROM $8)
+                RETURNING {}
+                "#,
+                WIDGET_COLUMNS
+            ))
+            .bind(id)
+            .bind(widget.area_id)
+            .bind(&widget.widget_type)
+            .bind(&widget.title)
+            .bind(&widget.content)
+            .bind(&widget.settings)
+            .bind(widget.is_active)
+            .bind(self.site_id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to update widget", e))
+        }
 
-            sqlx::query(
-                r#"
-                INSERT INTO options (id, site_id, option_name, option_value)
-                VALUES ($1, $2, $3, $4)
-                ON CONFLICT (option_name, site_id) DO UPDATE SET
-                    option_value = EXCLUDED.option_value,
-                    updated_at = NOW()
-                "#,
-            )
+        pub async fn delete_widget(&self, id: Uuid) -> Result<bool> {
+            let result = sqlx::query(&format!(
+                "UPDATE widgets SET deleted_at = NOW(), updated_at = NOW() \
+                 WHERE id = $1 AND deleted_at IS NULL AND {}",
+                SITE_WIDGETS
+            ))
             .bind(id)
             .bind(self.site_id)
-            .bind(name)
-            .bind(value)
             .execute(&self.pool)
             .await
-            .map_err(|e| Error::database_with_source("Failed to set option", e))?;
-
-            Ok(())
+            .map_err(|e| Error::database_with_source("Failed to delete widget", e))?;
+            Ok(result.rows_affected() > 0)
         }
 
-        /// Delete an option (only non-system options)
-        pub async fn delete(&self, name: &str) -> Result<bool> {
+        /// Put widgets into an area in the given order, moving them from
+        /// other areas of the site when needed. Returns how many were placed.
+        pub async fn place_widgets(&self, area_id: Uuid, widget_ids: &[Uuid]) -> Result<u64> {
+            let mut tx = self
+                .pool
+                .begin()
+                .await
+                .map_err(|e| Error::database_with_source("Failed to begin transaction", e))?;
+
             let query = format!(
-                "DELETE FROM options WHERE option_name = $1 AND {} AND is_system = false",
-                self.site_condition()
+                "UPDATE widgets SET area_id = $1, widget_order = $3, updated_at = NOW() \
+                 WHERE id = $4 AND deleted_at IS NULL AND {}",
+                SITE_WIDGETS
             );
+            let mut placed = 0;
+            for (position, id) in widget_ids.iter().enumerate() {
+                placed += sqlx::query(&query)
+                    .bind(area_id)
+                    .bind(self.site_id)
+                    .bind(position as i32)
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(|e| Error::database_with_source("Failed to place widget", e))?
+                    .rows_affected();
+            }
 
-            let result = sqlx::query(&query)
-                .bind(name)
-                .execute(&self.pool)
+            tx.commit()
                 .await
-                .map_err(|e| Error::database_with_source("Failed to delete option", e))?;
+                .map_err(|e| Error::database_with_source("Failed to place widgets", e))?;
+            Ok(placed)
+        }
+    }
+}
 
-            Ok(result.rows_affected() > 0)
+/// Sites of a network and the plugins active on each
+pub mod sites {
+    use super::*;
+    use chrono::{DateTime, Utc};
+
+    /// Site of the network
+    #[derive(Debug, Clone, PartialEq, sqlx::FromRow, serde::Serialize, serde::Deserialize)]
+    pub struct SiteRow {
+        pub id: Uuid,
+        pub slug: String,
+        pub name: String,
+        pub domain: Option<String>,
+        pub tenant: Option<String>,
+        /// `active`, `suspended` or `archived`
+        pub status: String,
+        pub status_reason: Option<String>,
+        pub owner_id: Option<Uuid>,
+        pub status_changed_at: Option<DateTime<Utc>>,
+        pub created_at: DateTime<Utc>,
+        pub updated_at: DateTime<Utc>,
+    }
+
+    const SITE_COLUMNS: &str = "id, slug, name, domain, tenant, status, status_reason, \
+         owner_id, status_changed_at, created_at, updated_at";
+
+    /// Site fields to save
+    #[derive(Debug, Clone)]
+    pub struct SaveSite {
+        pub slug: String,
+        pub name: String,
+        pub domain: Option<String>,
+        pub tenant: Option<String>,
+        pub owner_id: Option<Uuid>,
+    }
+
+    pub struct SitesRepository {
+        pool: PgPool,
+    }
+
+    impl SitesRepository {
+        pub fn new(pool: PgPool) -> Self {
+            Self { pool }
         }
 
-        /// Get all options that should be autoloaded
-        pub async fn get_autoload(&self) -> Result<Vec<OptionRow>> {
-            let query = format!(
-                "SELECT * FROM options WHERE autoload = true AND ({} OR site_id IS NULL) ORDER BY option_group, option_name",
-                self.site_condition()
-            );
+        fn save_error(e: sqlx::Error) -> Error {
+            match &e {
+                sqlx::Error::Database(db) if db.code().as_deref() == Some("23505") => {
+                    let field = match db.constraint() {
+                        Some(c) if c.contains("domain") => "domain",
+                        _ => "slug",
+                    };
+                    Error::Duplicate {
+                        entity_type: "Site".to_string(),
+                        field: field.to_string(),
+                    }
+                }
+                _ => Error::database_with_source("Failed to save site", e),
+            }
+        }
 
-            sqlx::query_as::<_, OptionRow>(&query)
+        /// All sites by slug
+        pub async fn list(&self) -> Result<Vec<SiteRow>> {
+            sqlx::query_as(&format!("SELECT {} FROM sites ORDER BY slug", SITE_COLUMNS))
                 .fetch_all(&self.pool)
                 .await
-                .map_err(|e| Error::database_with_source("Failed to get autoload options", e))
+                .map_err(|e| Error::database_with_source("Failed to list sites", e))
         }
 
-        /// Get all options
-        pub async fn get_all(&self) -> Result<Vec<OptionRow>> {
-            let query = format!(
-                "SELECT * FROM options WHERE ({} OR site_id IS NULL) ORDER BY option_group, option_name",
-                self.site_condition()
-            );
-
-            sqlx::query_as::<_, OptionRow>(&query)
-                .fetch_all(&self.pool)
+        pub async fn find(&self, id: Uuid) -> Result<Option<SiteRow>> {
+            sqlx::query_as(&format!("SELECT {} FROM sites WHERE id = $1", SITE_COLUMNS))
+                .bind(id)
+                .fetch_optional(&self.pool)
                 .await
-                .map_err(|e| Error::database_with_source("Failed to get all options", e))
+                .map_err(|e| Error::database_with_source("Failed to get site", e))
         }
 
-        /// Get options by group
-        pub async fn get_by_group(&self, group: &str) -> Result<Vec<OptionRow>> {
-            let query = format!(
-                "SELECT * FROM options WHERE option_group = $1 AND ({} OR site_id IS NULL) ORDER BY option_name",
-                self.site_condition()
-            );
+        /// Site served under a slug or at a custom domain
+        pub async fn find_by_address(
+            &self,
+            slug: Option<&str>,
+            domain: Option<&str>,
+        ) -> Result<Option<SiteRow>> {
+            sqlx::query_as(&format!(
+                "SELECT {} FROM sites WHERE lower(domain) = lower($2) OR slug = $1 \
+                 ORDER BY lower(domain) = lower($2) DESC NULLS LAST LIMIT 1",
+                SITE_COLUMNS
+            ))
+            .bind(slug)
+            .bind(domain)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to resolve site", e))
+        }
 
-            sqlx::query_as::<_, OptionRow>(&query)
-                .bind(group)
-                .fetch_all(&self.pool)
-                .await
-                .map_err(|e| Error::database_with_source("Failed to get options by group", e))
+        /// Whether the main site has a theme installed
+        pub async fn theme_installed(&self, theme: &str) -> Result<bool> {
+            sqlx::query_scalar(
+                "SELECT EXISTS (SELECT 1 FROM themes \
+                 WHERE site_id IS NULL AND theme_id = $1 AND is_installed)",
+            )
+            .bind(theme)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to check theme", e))
         }
 
-        /// Get all options grouped by their option_group
-        pub async fn get_all_grouped(&self) -> Result<Vec<SettingsGroup>> {
-            let options = self.get_all().await?;
+        /// Create a site with the themes the main site has installed;
+        /// `theme` is active, or else the theme of the main site
+        pub async fn create(&self, site: &SaveSite, theme: Option<&str>) -> Result<SiteRow> {
+            let mut tx = self
+                .pool
+                .begin()
+                .await
+                .map_err(|e| Error::database_with_source("Failed to begin transaction", e))?;
 
-            let mut groups: std::collections::HashMap<String, Vec<OptionRow>> =
-                std::collections::HashMap::new();
-            for option in options {
-                groups
-                    .entry(option.option_group.clone())
-                    .or_default()
-                    .push(option);
-            }
+            let row: SiteRow = sqlx::query_as(&format!(
+                r#"
+                INSERT INTO sites (id, slug, name, domain, tenant, owner_id)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                RETURNING {}
+                "#,
+                SITE_COLUMNS
+            ))
+            .bind(Uuid::now_v7())
+            .bind(&site.slug)
+            .bind(&site.name)
+            .bind(&site.domain)
+            .bind(&site.tenant)
+            .bind(site.owner_id)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(Self::save_error)?;
 
-            let mut result: Vec<SettingsGroup> = groups
-                .into_iter()
-                .map(|(group, settings)| SettingsGroup { group, settings })
-                .collect();
+            sqlx::query(
+                r#"
+                INSERT INTO themes (
+                    id, site_id, theme_id, name, description, version, author, author_url,
+                    license, is_active, is_installed, parent_theme_id, screenshot_url,
+                    homepage_url, tags, supports, menu_locations, widget_areas,
+                    customizer_schema, settings, template_count, activated_at, installed_at
+                )
+                SELECT
+                    uuid_generate_v4(), $1, theme_id, name, description, version, author,
+                    author_url, license, theme_id = active.theme, is_installed, parent_theme_id,
+                    screenshot_url, homepage_url, tags, supports, menu_locations, widget_areas,
+                    customizer_schema, '{}'::jsonb, template_count,
+                    CASE WHEN theme_id = active.theme THEN NOW() END, NOW()
+                FROM themes,
+                    (SELECT COALESCE($2, (
+                        SELECT theme_id FROM themes WHERE site_id IS NULL AND is_active LIMIT 1
+                    )) AS theme) active
+                WHERE site_id IS NULL AND is_installed
+                ON CONFLICT (site_id, theme_id) DO NOTHING
+                "#,
+            )
+            .bind(row.id)
+            .bind(theme)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to install site themes", e))?;
 
-            // Sort groups in a logical order
-            let group_order = [
-                "general",
-                "reading",
-                "discussion",
-                "media",
-                "permalinks",
-                "privacy",
-                "security",
-            ];
-            result.sort_by(|a, b| {
-                let a_idx = group_order
-                    .iter()
-                    .position(|&g| g == a.group)
-                    .unwrap_or(999);
-                let b_idx = group_order
-                    .iter()
-                    .position(|&g| g == b.group)
-                    .unwrap_or(999);
-                a_idx.cmp(&b_idx)
-            });
+            tx.commit()
+                .await
+                .map_err(|e| Error::database_with_source("Failed to create site", e))?;
+            Ok(row)
+        }
 
-            Ok(result)
+        pub async fn update(&self, id: Uuid, site: &SaveSite) -> Result<Option<SiteRow>> {
+            sqlx::query_as(&format!(
+                r#"
+                UPDATE sites SET
+                    slug = $2, name = $3, domain = $4, tenant = $5, owner_id = $6,
+                    updated_at = NOW()
+                WHERE id = $1
+                RETURNING {}
+                "#,
+                SITE_COLUMNS
+            ))
+            .bind(id)
+            .bind(&site.slug)
+            .bind(&site.name)
+            .bind(&site.domain)
+            .bind(&site.tenant)
+            .bind(site.owner_id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(Self::save_error)
         }
 
-        /// Batch update multiple options
-        pub async fn batch_update(&self, updates: Vec<(String, serde_json::Value)>) -> Result<u64> {
-            let mut updated = 0u64;
+        /// Move a site to `active`, `suspended` or `archived`
+        pub async fn set_status(
+            &self,
+            id: Uuid,
+            status: &str,
+            reason: Option<&str>,
+        ) -> Result<Option<SiteRow>> {
+            sqlx::query_as(&format!(
+                r#"
+                UPDATE sites SET
+                    status = $2, status_reason = $3, status_changed_at = NOW(),
+                    updated_at = NOW()
+                WHERE id = $1
+                RETURNING {}
+                "#,
+                SITE_COLUMNS
+            ))
+            .bind(id)
+            .bind(status)
+            .bind(reason)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to change site status", e))
+        }
 
-            for (name, value) in updates {
-                sqlx::query(
-                    r#"
-                    UPDATE options
-                    SET option_value = $1, updated_at = NOW()
-                    WHERE option_name = $2 AND (site_id = $3 OR ($3 IS NULL AND site_id IS NULL))
-                    "#,
-                )
-                .bind(&value)
-                .bind(&name)
-                .bind(self.site_id)
+        /// Plugins active on a site alone
+        pub async fn plugins(&self, site_id: Uuid) -> Result<Vec<String>> {
+            sqlx::query_scalar(
+                "SELECT plugin_id FROM site_plugins WHERE site_id = $1 ORDER BY plugin_id",
+            )
+            .bind(site_id)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to list site plugins", e))
+        }
+
+        pub async fn set_plugin(&self, site_id: Uuid, plugin_id: &str, active: bool) -> Result<()> {
+            let query = if active {
+                "INSERT INTO site_plugins (site_id, plugin_id) VALUES ($1, $2) \
+                 ON CONFLICT (site_id, plugin_id) DO NOTHING"
+            } else {
+                "DELETE FROM site_plugins WHERE site_id = $1 AND plugin_id = $2"
+            };
+            sqlx::query(query)
+                .bind(site_id)
+                .bind(plugin_id)
                 .execute(&self.pool)
                 .await
-                .map_err(|e| Error::database_with_source("Failed to batch update options", e))?;
-
-                updated += 1;
-            }
+                .map_err(|e| Error::database_with_source("Failed to save site plugin", e))?;
+            Ok(())
+        }
 
-            Ok(updated)
+        /// Whether a user owns a site or was added to it
+        pub async fn is_member(&self, site_id: Uuid, user_id: Uuid) -> Result<bool> {
+            sqlx::query_scalar(
+                "SELECT EXISTS (SELECT 1 FROM sites WHERE id = $1 AND owner_id = $2) \
+                 OR EXISTS (SELECT 1 FROM site_users WHERE site_id = $1 AND user_id = $2)",
+            )
+            .bind(site_id)
+            .bind(user_id)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to check site membership", e))
         }
 
-        /// Get available option groups
-        pub async fn get_groups(&self) -> Result<Vec<String>> {
-            let query = format!(
-                "SELECT DISTINCT option_group FROM options WHERE ({} OR site_id IS NULL) ORDER BY option_group",
-                self.site_condition()
-            );
+        /// Users added to a site, besides its owner
+        pub async fn members(&self, site_id: Uuid) -> Result<Vec<Uuid>> {
+            sqlx::query_scalar(
+                "SELECT user_id FROM site_users WHERE site_id = $1 ORDER BY added_at, user_id",
+            )
+            .bind(site_id)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to list site members", e))
+        }
 
-            let rows: Vec<(String,)> = sqlx::query_as(&query)
-                .fetch_all(&self.pool)
+        pub async fn set_member(&self, site_id: Uuid, user_id: Uuid, member: bool) -> Result<()> {
+            let query = if member {
+                "INSERT INTO site_users (site_id, user_id) VALUES ($1, $2) \
+                 ON CONFLICT (site_id, user_id) DO NOTHING"
+            } else {
+                "DELETE FROM site_users WHERE site_id = $1 AND user_id = $2"
+            };
+            sqlx::query(query)
+                .bind(site_id)
+                .bind(user_id)
+                .execute(&self.pool)
                 .await
-                .map_err(|e| Error::database_with_source("Failed to get option groups", e))?;
-
-            Ok(rows.into_iter().map(|(g,)| g).collect())
+                .map_err(|e| match &e {
+                    sqlx::Error::Database(db) if db.code().as_deref() == Some("23503") => {
+                        Error::not_found("User", user_id.to_string())
+                    }
+                    _ => Error::database_with_source("Failed to save site member", e),
+                })?;
+            Ok(())
         }
     }
 }
@@ -867,6 +2260,8 @@ pub mod comments {
         pub user_id: Option<Uuid>,
         pub status: Option<CommentStatus>,
         pub search: Option<String>,
+        /// Filter expression, e.g. `status:approved AND parent:null`
+        pub filter: Option<String>,
         pub include_deleted: bool,
         pub order_by: Option<String>,
         pub order_desc: bool,
@@ -902,16 +2297,39 @@ pub mod comments {
         pub user_avatar_url: Option<String>,
     }
 
+    /// Comment settings of a post; `None` overrides inherit the site-wide
+    /// discussion settings
+    #[derive(Debug, Clone, sqlx::FromRow)]
+    pub struct PostCommentSettingsRow {
+        pub post_id: Uuid,
+        pub post_status: String,
+        /// `open` or `closed`
+        pub comment_status: Option<String>,
+        pub require_moderation: Option<bool>,
+        pub max_depth: Option<i32>,
+        pub closes_at: Option<DateTime<Utc>>,
+    }
+
+    /// New comment settings for a post
+    #[derive(Debug, Clone)]
+    pub struct SavePostCommentSettings {
+        pub comment_status: String,
+        pub require_moderation: Option<bool>,
+        pub max_depth: Option<i32>,
+        pub closes_at: Option<DateTime<Utc>>,
+    }
+
     pub struct CommentsRepository {
         pool: PgPool,
         site_id: Option<Uuid>,
     }
 
     impl CommentsRepository {
+        /// Repository for the site the current request serves
         pub fn new(pool: PgPool) -> Self {
             Self {
                 pool,
-                site_id: None,
+                site_id: current_site(),
             }
         }
 
@@ -1180,6 +2598,9 @@ pub mod comments {
                 ));
             }
 
+            conditions
+                .extend(crate::filter::COMMENT_FILTERS.parse_conditions(params.filter.as_deref())?);
+
             let where_clause = conditions.join(" AND ");
             let order_by = params.order_by.as_deref().unwrap_or("created_at");
             let order_dir = if params.order_desc { "DESC" } else { "ASC" };
@@ -1490,6 +2911,72 @@ pub mod comments {
 
             Ok(result.0)
         }
+
+        /// Comment settings of a post, `None` if the post does not exist
+        pub async fn find_post_settings(
+            &self,
+            post_id: Uuid,
+        ) -> Result<Option<PostCommentSettingsRow>> {
+            sqlx::query_as::<_, PostCommentSettingsRow>(
+                r#"
+                SELECT p.id AS post_id, p.status::text AS post_status, p.comment_status,
+                    s.require_moderation, s.max_depth, s.closes_at
+                FROM posts p
+                LEFT JOIN post_comment_settings s ON s.post_id = p.id
+                WHERE p.id = $1 AND p.deleted_at IS NULL
+                "#,
+            )
+            .bind(post_id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to get post comment settings", e))
+        }
+
+        /// Replace the comment settings of a post, `None` if the post does
+        /// not exist
+        pub async fn save_post_settings(
+            &self,
+            post_id: Uuid,
+            settings: &SavePostCommentSettings,
+        ) -> Result<Option<PostCommentSettingsRow>> {
+            let save_error =
+                |e| Error::database_with_source("Failed to save post comment settings", e);
+            let mut tx = self.pool.begin().await.map_err(save_error)?;
+
+            let updated = sqlx::query(
+                "UPDATE posts SET comment_status = $2, updated_at = NOW() WHERE id = $1 AND deleted_at IS NULL",
+            )
+            .bind(post_id)
+            .bind(&settings.comment_status)
+            .execute(&mut *tx)
+            .await
+            .map_err(save_error)?;
+            if updated.rows_affected() == 0 {
+                return Ok(None);
+            }
+
+            sqlx::query(
+                r#"
+                INSERT INTO post_comment_settings (post_id, require_moderation, max_depth, closes_at, updated_at)
+                VALUES ($1, $2, $3, $4, NOW())
+                ON CONFLICT (post_id) DO UPDATE SET
+                    require_moderation = EXCLUDED.require_moderation,
+                    max_depth = EXCLUDED.max_depth,
+                    closes_at = EXCLUDED.closes_at,
+                    updated_at = NOW()
+                "#,
+            )
+            .bind(post_id)
+            .bind(settings.require_moderation)
+            .bind(settings.max_depth)
+            .bind(settings.closes_at)
+            .execute(&mut *tx)
+            .await
+            .map_err(save_error)?;
+
+            tx.commit().await.map_err(save_error)?;
+            self.find_post_settings(post_id).await
+        }
     }
 }
 
@@ -1589,10 +3076,11 @@ pub mod themes {
     }
 
     impl ThemeRepository {
+        /// Repository for the site the current request serves
         pub fn new(pool: PgPool) -> Self {
             Self {
                 pool,
-                site_id: None,
+                site_id: current_site(),
             }
         }
 
@@ -2054,9 +3542,104 @@ mod tests {
         assert!(condition.contains("content ILIKE"));
     }
 
+    #[test]
+    fn test_versioning_guard_and_conflict() {
+        assert_eq!(Versioning::guard(3), "($3::BIGINT IS NULL OR version = $3)");
+        assert!(matches!(
+            Versioning::conflict_error("Post", "1", Some(7)),
+            Error::VersionConflict {
+                current_version: 7,
+                ..
+            }
+        ));
+        assert!(matches!(
+            Versioning::conflict_error("Post", "1", None),
+            Error::NotFound { .. }
+        ));
+    }
+
     #[test]
     fn test_escape_like() {
         assert_eq!(QueryHelper::escape_like("test%"), "test\\%");
         assert_eq!(QueryHelper::escape_like("test_"), "test\\_");
     }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_options_on_sqlite() {
+        use options::OptionsRepository;
+
+        let pool = ProviderPool::Sqlite(
+            sqlx::sqlite::SqlitePoolOptions::new()
+                .max_connections(1)
+                .connect("sqlite::memory:")
+                .await
+                .unwrap(),
+        );
+        pool.execute(
+            "CREATE TABLE options (id TEXT PRIMARY KEY, site_id TEXT, \
+             option_name TEXT NOT NULL, option_value TEXT, \
+             option_group TEXT NOT NULL DEFAULT 'general', \
+             autoload BOOLEAN NOT NULL DEFAULT TRUE, is_system BOOLEAN NOT NULL DEFAULT FALSE, \
+             value_type TEXT, validation TEXT, display_name TEXT, description TEXT, \
+             created_at DATETIME NOT NULL, updated_at DATETIME NOT NULL, \
+             version INTEGER NOT NULL DEFAULT 1, UNIQUE (site_id, option_name))",
+            &[],
+        )
+        .await
+        .unwrap();
+
+        let repo = OptionsRepository::from_provider(pool).with_site(Uuid::new_v4());
+        repo.set("blogname", serde_json::json!("One"))
+            .await
+            .unwrap();
+        repo.set("blogname", serde_json::json!("Two"))
+            .await
+            .unwrap();
+        assert_eq!(
+            repo.get("blogname").await.unwrap(),
+            Some(serde_json::json!("Two"))
+        );
+        assert_eq!(repo.get_full("blogname").await.unwrap().unwrap().version, 2);
+
+        let stale = repo
+            .set_versioned("blogname", serde_json::json!("Three"), Some(1))
+            .await;
+        assert!(matches!(
+            stale,
+            Err(Error::VersionConflict {
+                current_version: 2,
+                ..
+            })
+        ));
+        let updated = repo
+            .set_versioned("blogname", serde_json::json!("Three"), Some(2))
+            .await
+            .unwrap();
+        assert_eq!(updated.version, 3);
+
+        let rows = repo
+            .batch_update_versioned(vec![
+                ("blogname".to_string(), serde_json::json!("Four"), Some(3)),
+                ("missing".to_string(), serde_json::json!(true), None),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].option_value, Some(serde_json::json!("Four")));
+
+        let conflict = repo
+            .batch_update_versioned(vec![
+                ("blogname".to_string(), serde_json::json!("Five"), Some(4)),
+                ("missing".to_string(), serde_json::json!(true), Some(1)),
+            ])
+            .await;
+        assert!(matches!(conflict, Err(Error::NotFound { .. })));
+        assert_eq!(
+            repo.get("blogname").await.unwrap(),
+            Some(serde_json::json!("Four"))
+        );
+        assert_eq!(repo.get_groups().await.unwrap(), vec!["general"]);
+        assert!(repo.delete("blogname").await.unwrap());
+    }
 }
@@ -3,7 +3,9 @@
 //! Request and response types for the editor REST API.
 
 use crate::blocks::{Block, BlockId, BlockType};
-use crate::post::{Author, FeaturedMedia, PostDocument, PostStats, PublishStatus};
+use crate::post::{
+    Author, EmbargoWindow, FeaturedMedia, PostDocument, PostStats, PublishStatus, ScheduleTime,
+};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -134,12 +136,22 @@ pub struct FeaturedMediaRequest {
 }
 
 /// Update publishing request
+///
+/// Dates without an offset are read in `timezone`, or the post's timezone.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UpdatePublishingRequest {
     pub status: Option<PublishStatus>,
-    pub publish_at: Option<DateTime<Utc>>,
+    pub publish_at: Option<ScheduleTime>,
     pub password: Option<String>,
     pub visibility: Option<String>,
+    /// IANA timezone, e.g. `Europe/Berlin`
+    pub timezone: Option<String>,
+    /// When the post goes back to draft
+    pub expires_at: Option<ScheduleTime>,
+    /// Start of the embargo; right away if only the end is given
+    pub embargo_starts_at: Option<ScheduleTime>,
+    /// When the embargo lifts
+    pub embargo_ends_at: Option<ScheduleTime>,
 }
 
 /// Post response
@@ -197,6 +209,9 @@ impl From<PostDocument> for PostResponse {
                 status: doc.publishing.status,
                 published_at: doc.publishing.published_at,
                 scheduled_at: doc.publishing.scheduled_at,
+                expires_at: doc.publishing.expires_at,
+                embargo: doc.publishing.embargo,
+                timezone: doc.publishing.timezone,
                 visibility: format!("{:?}", doc.publishing.visibility).to_lowercase(),
             },
             stats: doc.stats,
@@ -238,6 +253,9 @@ pub struct PublishingResponse {
     pub status: PublishStatus,
     pub published_at: Option<DateTime<Utc>>,
     pub scheduled_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub embargo: Option<EmbargoWindow>,
+    pub timezone: Option<String>,
     pub visibility: String,
 }
 
@@ -589,7 +607,10 @@ pub struct AutosaveResponse {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PublishPostRequest {
     /// Publish immediately or schedule
-    pub publish_at: Option<DateTime<Utc>>,
+    pub publish_at: Option<ScheduleTime>,
+
+    /// Timezone `publish_at` is read in when it has no offset
+    pub timezone: Option<String>,
 
     /// Password protection
     pub password: Option<String>,
@@ -601,7 +622,10 @@ pub struct PublishPostRequest {
 /// Schedule post request
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SchedulePostRequest {
-    pub publish_at: DateTime<Utc>,
+    pub publish_at: ScheduleTime,
+    /// Timezone `publish_at` is read in when it has no offset
+    pub timezone: Option<String>,
+    pub expires_at: Option<ScheduleTime>,
 }
 
 /// API error response
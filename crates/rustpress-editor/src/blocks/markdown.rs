@@ -0,0 +1,956 @@
+//! Markdown Import/Export
+//!
+//! Parses CommonMark with the GFM extensions (tables, footnotes, task lists,
+//! strikethrough) into block trees and writes blocks back the same way, so
+//! posts can be kept as Markdown files in git.
+//!
+//! A block is only written as Markdown when the Markdown parses back to the
+//! same block. Anything else — styled blocks, layout containers, media with
+//! captions — is written as a comment holding the block's JSON:
+//!
+//! ```text
+//! <!-- rustpress:block {"id":"...","type":"columns",...} -->
+//! ```
+//!
+//! which renders as nothing in other Markdown tools and is parsed back into
+//! the original block.
+
+use crate::blocks::{Block, BlockType, ListType, TableData};
+use pulldown_cmark::{Alignment, CodeBlockKind, Event, Options, Parser, Tag, TagEnd};
+use regex::Regex;
+use serde_json::Value;
+use std::sync::LazyLock;
+
+/// Start of the comment a block Markdown can't express is written as
+pub const BLOCK_COMMENT_PREFIX: &str = "<!-- rustpress:block ";
+
+const BLOCK_COMMENT_SUFFIX: &str = " -->";
+
+/// Written between two lists so they are not read back as one
+const LIST_SEPARATOR: &str = "<!-- -->";
+
+/// Prefix of the anchor footnote definitions are given
+const FOOTNOTE_ANCHOR_PREFIX: &str = "fn-";
+
+/// Custom attribute holding a task list item's state
+const CHECKED_ATTRIBUTE: &str = "checked";
+
+/// Custom attribute holding a table's column alignments
+const COLUMN_ALIGN_ATTRIBUTE: &str = "column_align";
+
+/// Inline HTML tags, with footnote references matched whole
+static TAG_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"<sup class="footnote-ref"><a href="\#fn-([^"<>]+)">[^<>]*</a></sup>|<[^<>]*>"#)
+        .unwrap()
+});
+
+static LINK_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"^<a href="([^"]*)"(?: title="([^"]*)")?>$"#).unwrap());
+
+static IMAGE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"^<img src="([^"]*)" alt="([^"]*)"(?: title="([^"]*)")?>$"#).unwrap()
+});
+
+static ENTITY_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^&(?:#[0-9]+|#[xX][0-9a-fA-F]+|[A-Za-z][A-Za-z0-9]*);").unwrap());
+
+static ORDERED_MARKER_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^([0-9]{1,9})([.)])").unwrap());
+
+fn options() -> Options {
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_FOOTNOTES);
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+    options.insert(Options::ENABLE_TASKLISTS);
+    options.insert(Options::ENABLE_HEADING_ATTRIBUTES);
+    options
+}
+
+// ============================================================================
+// Import
+// ============================================================================
+
+/// Parse Markdown into blocks
+pub fn parse(markdown: &str) -> Vec<Block> {
+    let mut reader = Reader {
+        events: Parser::new_ext(markdown, options()).collect(),
+        pos: 0,
+        footnotes: Vec::new(),
+        footnotes_block: None,
+    };
+    let mut blocks = reader.blocks(0);
+
+    if !reader.footnotes.is_empty() {
+        let footnotes = std::mem::take(&mut reader.footnotes);
+        match blocks
+            .iter_mut()
+            .find(|b| Some(b.id) == reader.footnotes_block)
+        {
+            Some(block) => block.children = footnotes,
+            None => {
+                let mut block = Block::new(BlockType::Footnotes);
+                block.children = footnotes;
+                blocks.push(block);
+            }
+        }
+    }
+    blocks
+}
+
+/// Walks the parser's events, building blocks
+struct Reader<'a> {
+    events: Vec<Event<'a>>,
+    pos: usize,
+    /// Footnote definitions, wherever they appeared
+    footnotes: Vec<Block>,
+    /// Top-level block the definitions are collected into, placed where the
+    /// first one appeared
+    footnotes_block: Option<uuid::Uuid>,
+}
+
+impl<'a> Reader<'a> {
+    fn next(&mut self) -> Option<Event<'a>> {
+        let event = self.events.get(self.pos).cloned();
+        self.pos += 1;
+        event
+    }
+
+    /// Blocks up to the end of the enclosing container
+    fn blocks(&mut self, depth: usize) -> Vec<Block> {
+        let mut blocks = Vec::new();
+        while let Some(event) = self.next() {
+            match event {
+                Event::End(_) => break,
+                Event::Rule => blocks.push(Block::new(BlockType::Separator)),
+                Event::Start(Tag::FootnoteDefinition(label)) => {
+                    let definition = self.footnote(&label, depth);
+                    self.footnotes.push(definition);
+                    if depth == 0 && self.footnotes_block.is_none() {
+                        let block = Block::new(BlockType::Footnotes);
+                        self.footnotes_block = Some(block.id);
+                        blocks.push(block);
+                    }
+                }
+                Event::Start(tag) => blocks.extend(self.block(tag, depth)),
+                _ => {}
+            }
+        }
+        blocks
+    }
+
+    fn block(&mut self, tag: Tag<'a>, depth: usize) -> Option<Block> {
+        match tag {
+            Tag::Paragraph => {
+                let events = self.inline(TagEnd::Paragraph);
+                Some(paragraph(&events))
+            }
+            Tag::Heading {
+                level, id, classes, ..
+            } => {
+                let events = self.inline(TagEnd::Heading(level));
+                let mut block = Block::new(BlockType::Heading);
+                block.attributes.level = Some(level as u8);
+                block.attributes.content = Some(render_inline(&events));
+                block.meta.anchor = id.map(|id| id.to_string());
+                block.css_classes = classes.into_iter().map(|c| c.to_string()).collect();
+                Some(block)
+            }
+            Tag::BlockQuote => {
+                let mut block = Block::new(BlockType::Quote);
+                block.children = self.blocks(depth + 1);
+                if block.children.len() == 1 && block.children[0].block_type == BlockType::Paragraph
+                {
+                    block.attributes.content = block.children.remove(0).attributes.content;
+                }
+                Some(block)
+            }
+            Tag::CodeBlock(kind) => {
+                let mut code = String::new();
+                while let Some(event) = self.next() {
+                    match event {
+                        Event::Text(text) => code.push_str(&text),
+                        Event::End(_) => break,
+                        _ => {}
+                    }
+                }
+                if code.ends_with('\n') {
+                    code.pop();
+                }
+                let mut block = Block::new(BlockType::Code);
+                if let CodeBlockKind::Fenced(info) = kind {
+                    if let Some(language) = info.split_whitespace().next() {
+                        block.attributes.language = Some(language.to_string());
+                    }
+                }
+                block.attributes.content = Some(code);
+                Some(block)
+            }
+            Tag::HtmlBlock => {
+                let mut html = String::new();
+                while let Some(event) = self.next() {
+                    match event {
+                        Event::Html(text) | Event::Text(text) => html.push_str(&text),
+                        Event::End(_) => break,
+                        _ => {}
+                    }
+                }
+                html_block(html.trim_end())
+            }
+            Tag::List(start) => Some(self.list(start, depth)),
+            Tag::Table(alignments) => Some(self.table(&alignments)),
+            _ => {
+                // Nothing else is block level; skip to its end
+                self.blocks(depth + 1);
+                None
+            }
+        }
+    }
+
+    /// Inline events up to `end`
+    fn inline(&mut self, end: TagEnd) -> Vec<Event<'a>> {
+        let mut events = Vec::new();
+        while let Some(event) = self.next() {
+            if event == Event::End(end) {
+                break;
+            }
+            events.push(event);
+        }
+        events
+    }
+
+    fn list(&mut self, start: Option<u64>, depth: usize) -> Block {
+        let mut block = Block::new(BlockType::List);
+        if let Some(start) = start {
+            block.attributes.list_type = Some(ListType::Ordered);
+            if start != 1 {
+                block.attributes.start = Some(start as u32);
+            }
+        }
+
+        while let Some(event) = self.next() {
+            match event {
+                Event::Start(Tag::Item) => {
+                    let item = self.item(depth);
+                    if item.attributes.custom.contains_key(CHECKED_ATTRIBUTE) {
+                        block.attributes.list_type = Some(ListType::Checklist);
+                    }
+                    block.children.push(item);
+                }
+                Event::End(_) => break,
+                _ => {}
+            }
+        }
+        block
+    }
+
+    fn item(&mut self, depth: usize) -> Block {
+        let mut block = Block::new(BlockType::ListItem);
+
+        // Tight items hold their text directly, not in a paragraph
+        let mut events = Vec::new();
+        while let Some(event) = self.events.get(self.pos) {
+            match event {
+                Event::TaskListMarker(checked) => {
+                    block
+                        .attributes
+                        .custom
+                        .insert(CHECKED_ATTRIBUTE.to_string(), Value::Bool(*checked));
+                }
+                Event::Start(tag) if !is_inline(tag) => break,
+                Event::End(TagEnd::Item) | Event::Rule => break,
+                event => events.push(event.clone()),
+            }
+            self.pos += 1;
+        }
+
+        block.children = self.blocks(depth + 1);
+        if !events.is_empty() {
+            block.attributes.content = Some(render_inline(&events));
+        } else if block
+            .children
+            .first()
+            .is_some_and(|c| c.block_type == BlockType::Paragraph)
+        {
+            block.attributes.content = block.children.remove(0).attributes.content;
+        }
+        block
+    }
+
+    fn footnote(&mut self, label: &str, depth: usize) -> Block {
+        let mut block = Block::new(BlockType::ListItem);
+        block.meta.anchor = Some(format!("{}{}", FOOTNOTE_ANCHOR_PREFIX, label));
+        block.children = self.blocks(depth + 1);
+        if block
+            .children
+            .first()
+            .is_some_and(|c| c.block_type == BlockType::Paragraph)
+        {
+            block.attributes.content = block.children.remove(0).attributes.content;
+        }
+        block
+    }
+
+    fn table(&mut self, alignments: &[Alignment]) -> Block {
+        let mut headers = Vec::new();
+        let mut rows = Vec::new();
+        while let Some(event) = self.next() {
+            match event {
+                Event::Start(Tag::TableHead) => headers = self.cells(),
+                Event::Start(Tag::TableRow) => rows.push(self.cells()),
+                Event::End(_) => break,
+                _ => {}
+            }
+        }
+
+        let mut block = Block::new(BlockType::Table);
+        block.attributes.table_data = Some(TableData {
+            headers,
+            rows,
+            has_fixed_layout: false,
+            has_header_row: true,
+            has_footer_row: false,
+        });
+        if alignments.iter().any(|a| *a != Alignment::None) {
+            let alignments = alignments
+                .iter()
+                .map(|a| Value::String(alignment_name(*a).to_string()))
+                .collect();
+            block
+                .attributes
+                .custom
+                .insert(COLUMN_ALIGN_ATTRIBUTE.to_string(), Value::Array(alignments));
+        }
+        block
+    }
+
+    /// Cells of a table row
+    fn cells(&mut self) -> Vec<String> {
+        let mut cells = Vec::new();
+        while let Some(event) = self.next() {
+            match event {
+                Event::Start(Tag::TableCell) => {
+                    let events = self.inline(TagEnd::TableCell);
+                    cells.push(render_inline(&events));
+                }
+                Event::End(_) => break,
+                _ => {}
+            }
+        }
+        cells
+    }
+}
+
+fn is_inline(tag: &Tag) -> bool {
+    matches!(
+        tag,
+        Tag::Emphasis | Tag::Strong | Tag::Strikethrough | Tag::Link { .. } | Tag::Image { .. }
+    )
+}
+
+/// A paragraph, or an image block when the paragraph is just an image
+fn paragraph(events: &[Event]) -> Block {
+    if let (
+        Some(Event::Start(Tag::Image {
+            dest_url, title, ..
+        })),
+        Some(Event::End(TagEnd::Image)),
+    ) = (events.first(), events.last())
+    {
+        let alt_is_text = events[1..events.len() - 1]
+            .iter()
+            .all(|e| matches!(e, Event::Text(_)));
+        if title.is_empty() && alt_is_text {
+            let mut block = Block::new(BlockType::Image);
+            block.attributes.url = Some(dest_url.to_string());
+            block.attributes.alt = Some(plain_text(&events[1..events.len() - 1]));
+            return block;
+        }
+    }
+
+    let mut block = Block::new(BlockType::Paragraph);
+    block.attributes.content = Some(render_inline(events));
+    block
+}
+
+/// A raw HTML block, or the block a block comment holds
+fn html_block(html: &str) -> Option<Block> {
+    if html == LIST_SEPARATOR {
+        return None;
+    }
+    if let Some(json) = html
+        .strip_prefix(BLOCK_COMMENT_PREFIX)
+        .and_then(|rest| rest.strip_suffix(BLOCK_COMMENT_SUFFIX))
+    {
+        if let Ok(block) = serde_json::from_str::<Block>(json) {
+            return Some(block);
+        }
+    }
+
+    let mut block = Block::new(BlockType::Html);
+    block.attributes.content = Some(html.to_string());
+    Some(block)
+}
+
+fn alignment_name(alignment: Alignment) -> &'static str {
+    match alignment {
+        Alignment::None => "none",
+        Alignment::Left => "left",
+        Alignment::Center => "center",
+        Alignment::Right => "right",
+    }
+}
+
+/// Rich text content for inline events
+fn render_inline(events: &[Event]) -> String {
+    let mut html = String::new();
+    let mut i = 0;
+    while i < events.len() {
+        match &events[i] {
+            Event::Text(text) => html.push_str(&escape_text(text)),
+            Event::Code(code) => {
+                html.push_str("<code>");
+                html.push_str(&escape_text(code));
+                html.push_str("</code>");
+            }
+            Event::Html(raw) | Event::InlineHtml(raw) => html.push_str(raw),
+            Event::SoftBreak => html.push('\n'),
+            Event::HardBreak => html.push_str("<br>"),
+            Event::FootnoteReference(label) => {
+                let label = escape_attribute(label);
+                html.push_str(&format!(
+                    r##"<sup class="footnote-ref"><a href="#{}{}">{}</a></sup>"##,
+                    FOOTNOTE_ANCHOR_PREFIX, label, label
+                ));
+            }
+            Event::Start(Tag::Emphasis) => html.push_str("<em>"),
+            Event::End(TagEnd::Emphasis) => html.push_str("</em>"),
+            Event::Start(Tag::Strong) => html.push_str("<strong>"),
+            Event::End(TagEnd::Strong) => html.push_str("</strong>"),
+            Event::Start(Tag::Strikethrough) => html.push_str("<del>"),
+            Event::End(TagEnd::Strikethrough) => html.push_str("</del>"),
+            Event::Start(Tag::Link {
+                dest_url, title, ..
+            }) => {
+                html.push_str(&format!(r#"<a href="{}""#, escape_attribute(dest_url)));
+                if !title.is_empty() {
+                    html.push_str(&format!(r#" title="{}""#, escape_attribute(title)));
+                }
+                html.push('>');
+            }
+            Event::End(TagEnd::Link) => html.push_str("</a>"),
+            Event::Start(Tag::Image {
+                dest_url, title, ..
+            }) => {
+                // The alt text is everything up to the end of the image
+                let end = events[i..]
+                    .iter()
+                    .position(|e| *e == Event::End(TagEnd::Image))
+                    .map_or(events.len(), |p| i + p);
+                html.push_str(&format!(
+                    r#"<img src="{}" alt="{}""#,
+                    escape_attribute(dest_url),
+                    escape_attribute(&plain_text(&events[i + 1..end]))
+                ));
+                if !title.is_empty() {
+                    html.push_str(&format!(r#" title="{}""#, escape_attribute(title)));
+                }
+                html.push('>');
+                i = end;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    html
+}
+
+fn plain_text(events: &[Event]) -> String {
+    events
+        .iter()
+        .filter_map(|e| match e {
+            Event::Text(text) | Event::Code(text) => Some(text.as_ref()),
+            Event::SoftBreak | Event::HardBreak => Some(" "),
+            _ => None,
+        })
+        .collect()
+}
+
+fn escape_text(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn escape_attribute(text: &str) -> String {
+    escape_text(text).replace('"', "&quot;")
+}
+
+fn unescape_text(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&amp;", "&")
+}
+
+// ============================================================================
+// Export
+// ============================================================================
+
+/// Write blocks as Markdown
+pub fn write(blocks: &[Block]) -> String {
+    let writer = Writer {
+        footnote_labels: footnote_labels(blocks),
+    };
+    writer.blocks(blocks)
+}
+
+struct Writer {
+    /// Labels of the document's footnotes, defined when checking that a
+    /// block referring to them reads back the same
+    footnote_labels: Vec<String>,
+}
+
+impl Writer {
+    fn blocks(&self, blocks: &[Block]) -> String {
+        let mut parts: Vec<String> = Vec::new();
+        let mut previous_list = false;
+        for block in blocks {
+            let markdown = self.block(block);
+            let is_list =
+                !markdown.starts_with(BLOCK_COMMENT_PREFIX) && block.block_type == BlockType::List;
+            if is_list && previous_list {
+                parts.push(LIST_SEPARATOR.to_string());
+            }
+            previous_list = is_list;
+            parts.push(markdown);
+        }
+        parts.join("\n\n")
+    }
+
+    /// A block as Markdown, or as a block comment when the Markdown would
+    /// not read back as the same block
+    fn block(&self, block: &Block) -> String {
+        match self.markdown(block) {
+            Some(markdown) if self.reads_back(block, &markdown) => markdown,
+            _ => block_comment(block),
+        }
+    }
+
+    fn reads_back(&self, block: &Block, markdown: &str) -> bool {
+        let parsed = if block.block_type == BlockType::Footnotes || self.footnote_labels.is_empty()
+        {
+            parse(markdown)
+        } else {
+            let definitions = self
+                .footnote_labels
+                .iter()
+                .map(|label| format!("[^{}]: -", label))
+                .collect::<Vec<_>>()
+                .join("\n\n");
+            let mut parsed = parse(&format!("{}\n\n{}", markdown, definitions));
+            parsed.pop();
+            parsed
+        };
+        parsed.len() == 1 && comparable(&parsed[0]) == comparable(block)
+    }
+
+    fn markdown(&self, block: &Block) -> Option<String> {
+        let attributes = &block.attributes;
+        match block.block_type {
+            BlockType::Paragraph => Some(inline_markdown(attributes.content.as_deref()?)),
+            BlockType::Heading => {
+                let level = attributes.level.filter(|l| (1..=6).contains(l))?;
+                let mut markdown = format!(
+                    "{} {}",
+                    "#".repeat(level as usize),
+                    inline_markdown(attributes.content.as_deref()?)
+                );
+                let mut heading_attributes: Vec<String> = Vec::new();
+                if let Some(anchor) = &block.meta.anchor {
+                    heading_attributes.push(format!("#{}", anchor));
+                }
+                heading_attributes.extend(block.css_classes.iter().map(|c| format!(".{}", c)));
+                if !heading_attributes.is_empty() {
+                    markdown.push_str(&format!(" {{{}}}", heading_attributes.join(" ")));
+                }
+                Some(markdown)
+            }
+            BlockType::List => self.list(block),
+            BlockType::Quote => {
+                let mut parts = Vec::new();
+                if let Some(content) = &attributes.content {
+                    parts.push(inline_markdown(content));
+                }
+                if !block.children.is_empty() {
+                    parts.push(self.blocks(&block.children));
+                }
+                Some(prefix_lines(&parts.join("\n\n"), "> ", ">"))
+            }
+            BlockType::Code => {
+                let code = attributes.content.as_deref()?;
+                let language = attributes
+                    .language
+                    .as_deref()
+                    .filter(|l| *l != "plaintext")
+                    .unwrap_or("");
+                let fence = "`".repeat(longest_run(code, '`').max(2) + 1);
+                Some(format!("{}{}\n{}\n{}", fence, language, code, fence))
+            }
+            BlockType::Image => Some(format!(
+                "![{}]({})",
+                escape_markdown(attributes.alt.as_deref().unwrap_or(""), false),
+                link_destination(attributes.url.as_deref()?)
+            )),
+            BlockType::Separator => Some("---".to_string()),
+            BlockType::Table => self.table(block),
+            BlockType::Footnotes => self.footnotes(block),
+            BlockType::Html => attributes.content.clone(),
+            _ => None,
+        }
+    }
+
+    fn list(&self, block: &Block) -> Option<String> {
+        let list_type = block.attributes.list_type.unwrap_or(ListType::Unordered);
+        let start = block.attributes.start.unwrap_or(1);
+        let loose = block.children.iter().any(|item| {
+            item.children
+                .iter()
+                .any(|c| c.block_type != BlockType::List)
+        });
+
+        let mut items = Vec::new();
+        for (i, item) in block.children.iter().enumerate() {
+            if item.block_type != BlockType::ListItem {
+                return None;
+            }
+            let marker = match list_type {
+                ListType::Ordered => format!("{}. ", start as usize + i),
+                ListType::Unordered | ListType::Checklist => "- ".to_string(),
+            };
+            let checkbox = match item.attributes.custom.get(CHECKED_ATTRIBUTE) {
+                Some(Value::Bool(true)) => "[x] ",
+                Some(Value::Bool(false)) => "[ ] ",
+                _ => "",
+            };
+
+            let mut body = item
+                .attributes
+                .content
+                .as_deref()
+                .map(inline_markdown)
+                .unwrap_or_default();
+            if !item.children.is_empty() {
+                let separator = if item.children[0].block_type == BlockType::List && !loose {
+                    "\n"
+                } else {
+                    "\n\n"
+                };
+                body.push_str(separator);
+                body.push_str(&self.blocks(&item.children));
+            }
+
+            // Continuation lines line up with the item's text
+            let (first, rest) = body.split_once('\n').unwrap_or((&body, ""));
+            let mut lines = format!("{}{}{}", marker, checkbox, first);
+            if !rest.is_empty() || body.contains('\n') {
+                lines.push('\n');
+                lines.push_str(&prefix_lines(rest, &" ".repeat(marker.len()), ""));
+            }
+            items.push(lines);
+        }
+        Some(items.join(if loose { "\n\n" } else { "\n" }))
+    }
+
+    fn table(&self, block: &Block) -> Option<String> {
+        let table = block.attributes.table_data.as_ref()?;
+        if table.headers.is_empty() {
+            return None;
+        }
+        let alignments: Vec<&str> = match block.attributes.custom.get(COLUMN_ALIGN_ATTRIBUTE) {
+            Some(Value::Array(values)) => values.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+
+        let row = |cells: &[String]| {
+            let cells: Vec<String> = cells.iter().map(|c| inline_markdown(c)).collect();
+            format!("| {} |", cells.join(" | "))
+        };
+        let delimiters: Vec<&str> = (0..table.headers.len())
+            .map(|i| match alignments.get(i).copied() {
+                Some("left") => ":---",
+                Some("center") => ":---:",
+                Some("right") => "---:",
+                _ => "---",
+            })
+            .collect();
+
+        let mut lines = vec![
+            row(&table.headers),
+            format!("| {} |", delimiters.join(" | ")),
+        ];
+        lines.extend(table.rows.iter().map(|r| row(r)));
+        Some(lines.join("\n"))
+    }
+
+    fn footnotes(&self, block: &Block) -> Option<String> {
+        let mut definitions = Vec::new();
+        for item in &block.children {
+            let label = item
+                .meta
+                .anchor
+                .as_deref()?
+                .strip_prefix(FOOTNOTE_ANCHOR_PREFIX)?;
+            let mut definition = format!(
+                "[^{}]: {}",
+                label,
+                inline_markdown(item.attributes.content.as_deref()?)
+            );
+            if !item.children.is_empty() {
+                definition.push_str("\n\n");
+                definition.push_str(&prefix_lines(&self.blocks(&item.children), "    ", ""));
+            }
+            definitions.push(definition);
+        }
+        Some(definitions.join("\n\n"))
+    }
+}
+
+/// Labels of the footnotes defined in a document
+fn footnote_labels(blocks: &[Block]) -> Vec<String> {
+    blocks
+        .iter()
+        .filter(|b| b.block_type == BlockType::Footnotes)
+        .flat_map(|b| &b.children)
+        .filter_map(|item| {
+            item.meta
+                .anchor
+                .as_deref()?
+                .strip_prefix(FOOTNOTE_ANCHOR_PREFIX)
+        })
+        .map(str::to_string)
+        .collect()
+}
+
+/// A block with what Markdown can't be expected to keep — its id and
+/// timestamps — left out
+fn comparable(block: &Block) -> Value {
+    fn strip(value: &mut Value) {
+        if let Value::Object(map) = value {
+            map.remove("id");
+            if let Some(Value::Object(meta)) = map.get_mut("meta") {
+                meta.remove("created_at");
+                meta.remove("modified_at");
+            }
+            if let Some(Value::Array(children)) = map.get_mut("children") {
+                children.iter_mut().for_each(strip);
+            }
+        }
+    }
+
+    let mut value = serde_json::to_value(block).unwrap_or(Value::Null);
+    strip(&mut value);
+    value
+}
+
+fn block_comment(block: &Block) -> String {
+    // `-->` can only appear inside JSON strings, where `>` may be escaped
+    let json = serde_json::to_string(block)
+        .unwrap_or_default()
+        .replace("-->", "--\\u003e");
+    format!("{}{}{}", BLOCK_COMMENT_PREFIX, json, BLOCK_COMMENT_SUFFIX)
+}
+
+/// Markdown for rich text content. Tags Markdown has syntax for are
+/// converted; any other HTML is kept as inline HTML.
+fn inline_markdown(html: &str) -> String {
+    let mut markdown = String::new();
+    // Whether each open `<a>` was converted to link syntax
+    let mut links: Vec<Option<String>> = Vec::new();
+    let mut last = 0;
+    let mut tags = TAG_RE.captures_iter(html).peekable();
+
+    while let Some(captures) = tags.next() {
+        let whole = captures.get(0).unwrap();
+        push_text(&mut markdown, &html[last..whole.start()]);
+        last = whole.end();
+        let tag = whole.as_str();
+
+        if let Some(label) = captures.get(1) {
+            markdown.push_str(&format!("[^{}]", unescape_text(label.as_str())));
+            continue;
+        }
+        match tag {
+            "<strong>" | "</strong>" => markdown.push_str("**"),
+            "<em>" | "</em>" => markdown.push('*'),
+            "<del>" | "</del>" => markdown.push_str("~~"),
+            "<br>" => markdown.push_str("\\\n"),
+            "<code>" => {
+                // Code spans hold text only
+                let end = html[last..].find("</code>").map(|i| last + i);
+                match end.filter(|end| !html[last..*end].contains('<')) {
+                    Some(end) => {
+                        markdown.push_str(&code_span(&unescape_text(&html[last..end])));
+                        last = end + "</code>".len();
+                        while tags
+                            .peek()
+                            .is_some_and(|c| c.get(0).unwrap().start() < last)
+                        {
+                            tags.next();
+                        }
+                    }
+                    None => markdown.push_str(tag),
+                }
+            }
+            "</a>" => match links.pop() {
+                Some(Some(destination)) => {
+                    markdown.push_str("](");
+                    markdown.push_str(&destination);
+                    markdown.push(')');
+                }
+                _ => markdown.push_str(tag),
+            },
+            _ => {
+                if let Some(link) = LINK_RE.captures(tag) {
+                    let mut destination = link_destination(&unescape_text(&link[1]));
+                    if let Some(title) = link.get(2) {
+                        destination.push_str(&format!(
+                            " \"{}\"",
+                            unescape_text(title.as_str()).replace('"', "\\\"")
+                        ));
+                    }
+                    links.push(Some(destination));
+                    markdown.push('[');
+                } else if let Some(image) = IMAGE_RE.captures(tag) {
+                    let mut destination = link_destination(&unescape_text(&image[1]));
+                    if let Some(title) = image.get(3) {
+                        destination.push_str(&format!(
+                            " \"{}\"",
+                            unescape_text(title.as_str()).replace('"', "\\\"")
+                        ));
+                    }
+                    markdown.push_str(&format!(
+                        "![{}]({})",
+                        escape_markdown(&unescape_text(&image[2]), false),
+                        destination
+                    ));
+                } else {
+                    if tag.starts_with("<a ") || tag == "<a>" {
+                        links.push(None);
+                    }
+                    markdown.push_str(tag);
+                }
+            }
+        }
+    }
+    push_text(&mut markdown, &html[last..]);
+    markdown
+}
+
+/// Append text content, escaped so it reads back as text
+fn push_text(markdown: &mut String, text: &str) {
+    if text.is_empty() {
+        return;
+    }
+    let at_line_start = markdown.is_empty() || markdown.ends_with('\n');
+    markdown.push_str(&escape_markdown(&unescape_text(text), at_line_start));
+}
+
+/// Escape the characters Markdown would read as syntax
+fn escape_markdown(text: &str, at_line_start: bool) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut escaped = String::with_capacity(text.len());
+    let mut line_start = at_line_start;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if line_start {
+            line_start = false;
+            if matches!(c, '#' | '-' | '+' | '=') {
+                escaped.push('\\');
+                escaped.push(c);
+                i += 1;
+                continue;
+            }
+            let rest: String = chars[i..].iter().take(10).collect();
+            if let Some(marker) = ORDERED_MARKER_RE.captures(&rest) {
+                // `1.` starts a list; `1\.` doesn't
+                escaped.push_str(&marker[1]);
+                escaped.push('\\');
+                escaped.push_str(&marker[2]);
+                i += marker[0].len();
+                continue;
+            }
+        }
+
+        let needs_escape = match c {
+            '\\' | '`' | '*' | '[' | ']' | '<' | '>' | '~' | '|' => true,
+            '_' => {
+                // Underscores inside words never start emphasis
+                let before = i.checked_sub(1).map(|j| chars[j]);
+                let after = chars.get(i + 1);
+                !before.is_some_and(char::is_alphanumeric)
+                    || !after.is_some_and(|c| c.is_alphanumeric())
+            }
+            '&' => {
+                let rest: String = chars[i..].iter().take(34).collect();
+                ENTITY_RE.is_match(&rest)
+            }
+            _ => false,
+        };
+        if needs_escape {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+        line_start = c == '\n';
+        i += 1;
+    }
+    escaped
+}
+
+fn code_span(code: &str) -> String {
+    let fence = "`".repeat(longest_run(code, '`') + 1);
+    if code.starts_with('`') || code.ends_with('`') || code.starts_with(' ') {
+        format!("{} {} {}", fence, code, fence)
+    } else {
+        format!("{}{}{}", fence, code, fence)
+    }
+}
+
+/// A link destination, in angle brackets when it has spaces or parentheses
+fn link_destination(url: &str) -> String {
+    if url.contains([' ', '(', ')']) || url.is_empty() {
+        format!("<{}>", url.replace('<', "%3C").replace('>', "%3E"))
+    } else {
+        url.to_string()
+    }
+}
+
+fn longest_run(text: &str, c: char) -> usize {
+    let mut longest = 0;
+    let mut current = 0;
+    for ch in text.chars() {
+        if ch == c {
+            current += 1;
+            longest = longest.max(current);
+        } else {
+            current = 0;
+        }
+    }
+    longest
+}
+
+/// Prefix every line; blank lines get `blank` instead
+fn prefix_lines(text: &str, prefix: &str, blank: &str) -> String {
+    text.split('\n')
+        .map(|line| {
+            if line.is_empty() {
+                blank.to_string()
+            } else {
+                format!("{}{}", prefix, line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -8,6 +8,7 @@
 //! - Real-time validation
 //! - Multiple serialization formats (HTML, Markdown, JSON)
 
+pub mod markdown;
 pub mod registry;
 pub mod serialization;
 pub mod transform;
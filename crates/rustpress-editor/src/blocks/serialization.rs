@@ -2,8 +2,7 @@
 //!
 //! Convert blocks to/from various formats (HTML, Markdown, JSON).
 
-use crate::blocks::{Block, BlockStyles, BlockType, ListType, Spacing};
-use pulldown_cmark::{html, Options, Parser};
+use crate::blocks::{markdown, Block, BlockStyles, BlockType, ListType, Spacing};
 
 /// Block serializer for multiple formats
 #[derive(Debug, Clone, Default)]
@@ -479,104 +478,16 @@ impl BlockSerializer {
         text
     }
 
-    /// Convert blocks to Markdown
+    /// Convert blocks to Markdown; blocks Markdown can't express are kept
+    /// as block comments
     pub fn to_markdown(&self, blocks: &[Block]) -> String {
-        let mut md = String::new();
-        for block in blocks {
-            md.push_str(&self.block_to_markdown(block));
-            md.push_str("\n\n");
-        }
-        md.trim().to_string()
-    }
-
-    fn block_to_markdown(&self, block: &Block) -> String {
-        let content = block.attributes.content.as_deref().unwrap_or("");
-
-        match &block.block_type {
-            BlockType::Paragraph => content.to_string(),
-            BlockType::Heading => {
-                let level = block.attributes.level.unwrap_or(2);
-                let prefix = "#".repeat(level as usize);
-                format!("{} {}", prefix, content)
-            }
-            BlockType::List => {
-                let is_ordered = matches!(block.attributes.list_type, Some(ListType::Ordered));
-                block
-                    .children
-                    .iter()
-                    .enumerate()
-                    .map(|(i, child)| {
-                        let child_content = child.attributes.content.as_deref().unwrap_or("");
-                        if is_ordered {
-                            format!("{}. {}", i + 1, child_content)
-                        } else {
-                            format!("- {}", child_content)
-                        }
-                    })
-                    .collect::<Vec<_>>()
-                    .join("\n")
-            }
-            BlockType::ListItem => content.to_string(),
-            BlockType::Quote => {
-                let quote = content
-                    .lines()
-                    .map(|line| format!("> {}", line))
-                    .collect::<Vec<_>>()
-                    .join("\n");
-                if let Some(cite) = &block.attributes.citation {
-                    format!("{}\n> — {}", quote, cite)
-                } else {
-                    quote
-                }
-            }
-            BlockType::Code => {
-                let lang = block.attributes.language.as_deref().unwrap_or("");
-                format!("```{}\n{}\n```", lang, content)
-            }
-            BlockType::Image => {
-                let alt = block.attributes.alt.as_deref().unwrap_or("");
-                let url = block.attributes.url.as_deref().unwrap_or("");
-                format!("![{}]({})", alt, url)
-            }
-            BlockType::Separator => "---".to_string(),
-            BlockType::Html | BlockType::CustomHtml => content.to_string(),
-            _ => {
-                // For unsupported blocks, try to extract text content
-                let children_md: String = block
-                    .children
-                    .iter()
-                    .map(|c| self.block_to_markdown(c))
-                    .collect::<Vec<_>>()
-                    .join("\n\n");
-
-                if !content.is_empty() && !children_md.is_empty() {
-                    format!("{}\n\n{}", content, children_md)
-                } else if !content.is_empty() {
-                    content.to_string()
-                } else {
-                    children_md
-                }
-            }
-        }
+        markdown::write(blocks)
     }
 
-    /// Parse Markdown into blocks
+    /// Parse Markdown (CommonMark with GFM tables, footnotes and task lists)
+    /// into blocks
     pub fn from_markdown(&self, markdown: &str) -> Vec<Block> {
-        // Convert markdown to HTML first
-        let mut options = Options::empty();
-        options.insert(Options::ENABLE_TABLES);
-        options.insert(Options::ENABLE_STRIKETHROUGH);
-        options.insert(Options::ENABLE_TASKLISTS);
-
-        let parser = Parser::new_ext(markdown, options);
-        let mut html_output = String::new();
-        html::push_html(&mut html_output, parser);
-
-        // For now, return a single HTML block
-        // A more sophisticated parser would convert to proper blocks
-        let mut block = Block::new(BlockType::Html);
-        block.attributes.content = Some(html_output);
-        vec![block]
+        markdown::parse(markdown)
     }
 
     /// Convert blocks to JSON
@@ -1,9 +1,26 @@
 //! Publishing Workflow
 //!
 //! Status management, scheduling, and publishing workflow.
-
-use chrono::{DateTime, Utc};
+//!
+//! Besides a publish date, a post can carry an expiry date, after which it
+//! goes back to draft, and an embargo window it may not be shown in. Dates
+//! are submitted as [`ScheduleTime`]s: either with an offset, or as a
+//! wall-clock time in the post's timezone.
+
+use chrono::{
+    DateTime, Duration, FixedOffset, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, TimeZone,
+    Utc,
+};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Time of day undated posts get when placed on a calendar day
+pub const DEFAULT_CALENDAR_TIME: NaiveTime = match NaiveTime::from_hms_opt(9, 0, 0) {
+    Some(time) => time,
+    None => panic!("invalid default calendar time"),
+};
 
 /// Publishing settings and workflow
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,13 +37,17 @@ pub struct PostPublishing {
     /// Expiration date (auto-unpublish)
     pub expires_at: Option<DateTime<Utc>>,
 
+    /// Period the post is withheld in
+    #[serde(default)]
+    pub embargo: Option<EmbargoWindow>,
+
     /// Post visibility
     pub visibility: PostVisibility,
 
     /// Review workflow status
     pub review: Option<ReviewStatus>,
 
-    /// Timezone for display
+    /// IANA timezone wall-clock dates are given in, and shown in
     pub timezone: Option<String>,
 
     /// Publishing history
@@ -41,6 +62,7 @@ impl Default for PostPublishing {
             scheduled_at: None,
             published_at: None,
             expires_at: None,
+            embargo: None,
             visibility: PostVisibility::Public,
             review: None,
             timezone: None,
@@ -121,18 +143,198 @@ impl PostPublishing {
         });
     }
 
-    /// Check if should auto-publish (for scheduled posts)
-    pub fn should_publish(&self) -> bool {
-        if self.status != PublishStatus::Scheduled {
-            return false;
+    /// Publish at `at`: right away if it has passed, otherwise scheduled.
+    /// An embargo covering the publish time pushes it back to the
+    /// embargo's end.
+    pub fn publish_at(&mut self, at: DateTime<Utc>, now: DateTime<Utc>) {
+        let at = match self.embargo {
+            Some(embargo) if embargo.contains(at.max(now)) => embargo.ends_at,
+            _ => at,
+        };
+        if at > now {
+            self.schedule(at);
+        } else {
+            self.publish();
+            self.published_at = Some(at);
+        }
+    }
+
+    /// Move the publish date of a scheduled post
+    pub fn reschedule(&mut self, date: DateTime<Utc>) {
+        self.scheduled_at = Some(date);
+        self.history.push(PublishEvent {
+            event_type: PublishEventType::Rescheduled,
+            timestamp: Utc::now(),
+            user_id: None,
+            note: Some(format!("Rescheduled for {}", date)),
+        });
+    }
+
+    /// Where the post sits on an editorial calendar: its publish date once
+    /// published or scheduled, its planned date while a draft
+    pub fn calendar_date(&self) -> Option<DateTime<Utc>> {
+        match self.status {
+            PublishStatus::Published | PublishStatus::Private => self.published_at,
+            PublishStatus::Trash => None,
+            _ => self.scheduled_at,
+        }
+    }
+
+    /// `day` at the time of day the post sits at on the calendar, in its
+    /// timezone; [`DEFAULT_CALENDAR_TIME`] for posts without a date
+    pub fn same_time_on(&self, day: NaiveDate) -> Result<DateTime<Utc>, ScheduleError> {
+        let time = match self.calendar_date() {
+            Some(at) => {
+                let tz = parse_timezone(self.timezone.as_deref().unwrap_or("UTC"))?;
+                at.with_timezone(&tz).time()
+            }
+            None => DEFAULT_CALENDAR_TIME,
+        };
+        self.resolve(ScheduleTime::Local(day.and_time(time)))
+    }
+
+    /// Move the post to `at` on an editorial calendar. Drafts get a planned
+    /// date and stay drafts; scheduled posts only move to a future date
+    /// outside their embargo, published posts only into the past.
+    pub fn move_to(&mut self, at: DateTime<Utc>, now: DateTime<Utc>) -> Result<(), ScheduleError> {
+        match self.status {
+            PublishStatus::Draft | PublishStatus::AutoDraft | PublishStatus::Pending => {
+                self.scheduled_at = Some(at);
+            }
+            PublishStatus::Scheduled => {
+                if at <= now {
+                    return Err(ScheduleError::ScheduledInPast);
+                }
+                if let Some(embargo) = self.embargo.filter(|embargo| embargo.contains(at)) {
+                    return Err(ScheduleError::Embargoed(embargo.ends_at));
+                }
+                if self.expires_at.is_some_and(|expires_at| expires_at <= at) {
+                    return Err(ScheduleError::ExpiresBeforePublish);
+                }
+                self.reschedule(at);
+            }
+            PublishStatus::Published | PublishStatus::Private => {
+                if at > now {
+                    return Err(ScheduleError::PublishedInFuture);
+                }
+                self.published_at = Some(at);
+            }
+            PublishStatus::Trash => return Err(ScheduleError::Trashed),
         }
-        if let Some(scheduled) = self.scheduled_at {
-            Utc::now() >= scheduled
+        Ok(())
+    }
+
+    /// Set when the post goes back to draft; it must be after a scheduled
+    /// post goes live
+    pub fn set_expiry(&mut self, expires_at: Option<DateTime<Utc>>) -> Result<(), ScheduleError> {
+        if let (Some(expires_at), Some(goes_live)) = (expires_at, self.goes_live_at()) {
+            if expires_at <= goes_live {
+                return Err(ScheduleError::ExpiresBeforePublish);
+            }
+        }
+        self.expires_at = expires_at;
+        Ok(())
+    }
+
+    /// Withhold the post during `embargo`. A scheduled publish date inside
+    /// the window moves to its end.
+    pub fn set_embargo(&mut self, embargo: Option<EmbargoWindow>) -> Result<(), ScheduleError> {
+        if let Some(embargo) = embargo {
+            if self.goes_live_at().is_some_and(|at| embargo.contains(at)) {
+                if self.expires_at.is_some_and(|at| at <= embargo.ends_at) {
+                    return Err(ScheduleError::ExpiresBeforePublish);
+                }
+                self.reschedule(embargo.ends_at);
+            }
+        }
+        self.embargo = embargo;
+        Ok(())
+    }
+
+    /// Set the timezone wall-clock dates are resolved in
+    pub fn set_timezone(&mut self, timezone: Option<&str>) -> Result<(), ScheduleError> {
+        self.timezone = match timezone {
+            Some(name) => Some(parse_timezone(name)?.name().to_string()),
+            None => None,
+        };
+        Ok(())
+    }
+
+    /// Resolve a submitted date in the post's timezone
+    pub fn resolve(&self, time: ScheduleTime) -> Result<DateTime<Utc>, ScheduleError> {
+        time.resolve(self.timezone.as_deref())
+    }
+
+    /// Take the post down once it has expired
+    pub fn expire(&mut self) {
+        self.status = PublishStatus::Draft;
+        self.expires_at = None;
+        self.history.push(PublishEvent {
+            event_type: PublishEventType::Expired,
+            timestamp: Utc::now(),
+            user_id: None,
+            note: None,
+        });
+    }
+
+    /// Apply the transition due at `now`, if any: publish a scheduled post,
+    /// withhold a published one until its embargo ends, or take down an
+    /// expired one. The scheduling jobs make the same changes to stored
+    /// posts.
+    pub fn apply_due(&mut self, now: DateTime<Utc>) -> Option<PublishEventType> {
+        match self.status {
+            PublishStatus::Published if self.expires_at.is_some_and(|at| now >= at) => {
+                self.expire();
+                Some(PublishEventType::Expired)
+            }
+            PublishStatus::Published if self.is_embargoed(now) => {
+                let ends_at = self.embargo.map(|embargo| embargo.ends_at);
+                self.status = PublishStatus::Scheduled;
+                self.scheduled_at = ends_at;
+                self.history.push(PublishEvent {
+                    event_type: PublishEventType::Embargoed,
+                    timestamp: now,
+                    user_id: None,
+                    note: ends_at.map(|at| format!("Withheld until {}", at)),
+                });
+                Some(PublishEventType::Embargoed)
+            }
+            PublishStatus::Scheduled if self.is_due(now) => {
+                // A post withheld by an embargo keeps its original date
+                let published_at = self.published_at.filter(|at| *at <= now);
+                self.publish();
+                self.published_at = published_at.or(Some(now));
+                Some(PublishEventType::Published)
+            }
+            _ => None,
+        }
+    }
+
+    /// When a scheduled post goes live
+    fn goes_live_at(&self) -> Option<DateTime<Utc>> {
+        if self.status == PublishStatus::Scheduled {
+            self.scheduled_at
         } else {
-            false
+            None
         }
     }
 
+    fn is_due(&self, now: DateTime<Utc>) -> bool {
+        self.status == PublishStatus::Scheduled
+            && self.scheduled_at.is_some_and(|at| now >= at)
+            && !self.is_embargoed(now)
+    }
+
+    /// Check if the post is withheld by its embargo at `at`
+    pub fn is_embargoed(&self, at: DateTime<Utc>) -> bool {
+        self.embargo.is_some_and(|embargo| embargo.contains(at))
+    }
+
+    /// Check if should auto-publish (for scheduled posts)
+    pub fn should_publish(&self) -> bool {
+        self.is_due(Utc::now())
+    }
+
     /// Check if should auto-expire
     pub fn should_expire(&self) -> bool {
         if self.status != PublishStatus::Published {
@@ -155,10 +357,161 @@ impl PostPublishing {
         matches!(
             self.status,
             PublishStatus::Published | PublishStatus::Private
-        )
+        ) && !self.is_embargoed(Utc::now())
     }
 }
 
+/// Period a post may not be shown in, e.g. until a press embargo lifts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmbargoWindow {
+    /// Start of the embargo; `None` for right away
+    pub starts_at: Option<DateTime<Utc>>,
+    /// When the post may be shown again
+    pub ends_at: DateTime<Utc>,
+}
+
+impl EmbargoWindow {
+    pub fn new(
+        starts_at: Option<DateTime<Utc>>,
+        ends_at: DateTime<Utc>,
+    ) -> Result<Self, ScheduleError> {
+        if starts_at.is_some_and(|starts_at| starts_at >= ends_at) {
+            return Err(ScheduleError::EmptyEmbargo);
+        }
+        Ok(Self { starts_at, ends_at })
+    }
+
+    /// Check if `at` falls inside the embargo
+    pub fn contains(&self, at: DateTime<Utc>) -> bool {
+        self.starts_at.is_none_or(|starts_at| starts_at <= at) && at < self.ends_at
+    }
+}
+
+/// A date as submitted: with an offset (`2025-03-01T09:00:00+01:00`), or a
+/// wall-clock time in the post's timezone (`2025-03-01T09:00`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum ScheduleTime {
+    Absolute(DateTime<FixedOffset>),
+    Local(NaiveDateTime),
+}
+
+impl ScheduleTime {
+    /// The instant this time stands for. Wall-clock times are read in
+    /// `timezone` (UTC if unset); one repeated when clocks go back means
+    /// its first occurrence, and one skipped when they go forward is an
+    /// error.
+    pub fn resolve(&self, timezone: Option<&str>) -> Result<DateTime<Utc>, ScheduleError> {
+        let local = match self {
+            Self::Absolute(at) => return Ok(at.with_timezone(&Utc)),
+            Self::Local(local) => local,
+        };
+        let tz = parse_timezone(timezone.unwrap_or("UTC"))?;
+        match tz.from_local_datetime(local) {
+            LocalResult::Single(at) | LocalResult::Ambiguous(at, _) => Ok(at.with_timezone(&Utc)),
+            LocalResult::None => Err(ScheduleError::NonexistentLocalTime(
+                *local,
+                tz.name().to_string(),
+            )),
+        }
+    }
+}
+
+impl From<DateTime<Utc>> for ScheduleTime {
+    fn from(at: DateTime<Utc>) -> Self {
+        Self::Absolute(at.fixed_offset())
+    }
+}
+
+impl FromStr for ScheduleTime {
+    type Err = ScheduleError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        if let Ok(at) = DateTime::parse_from_rfc3339(value) {
+            return Ok(Self::Absolute(at));
+        }
+        [
+            "%Y-%m-%dT%H:%M:%S%.f",
+            "%Y-%m-%dT%H:%M",
+            "%Y-%m-%d %H:%M:%S",
+            "%Y-%m-%d %H:%M",
+        ]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .map(Self::Local)
+        .ok_or_else(|| ScheduleError::InvalidTime(value.to_string()))
+    }
+}
+
+impl TryFrom<String> for ScheduleTime {
+    type Error = ScheduleError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl fmt::Display for ScheduleTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Absolute(at) => f.write_str(&at.to_rfc3339()),
+            Self::Local(local) => write!(f, "{}", local.format("%Y-%m-%dT%H:%M:%S")),
+        }
+    }
+}
+
+impl From<ScheduleTime> for String {
+    fn from(time: ScheduleTime) -> Self {
+        time.to_string()
+    }
+}
+
+/// Look up an IANA timezone such as `Europe/Berlin`
+pub fn parse_timezone(name: &str) -> Result<Tz, ScheduleError> {
+    name.trim()
+        .parse()
+        .map_err(|_| ScheduleError::UnknownTimezone(name.to_string()))
+}
+
+/// Calendar day `at` falls on in `timezone` (UTC if unset)
+pub fn local_date(at: DateTime<Utc>, timezone: Option<&str>) -> Result<NaiveDate, ScheduleError> {
+    let tz = parse_timezone(timezone.unwrap_or("UTC"))?;
+    Ok(at.with_timezone(&tz).date_naive())
+}
+
+/// When `day` starts in `timezone` (UTC if unset); where clocks skip
+/// midnight, the day starts an hour later
+pub fn day_start(day: NaiveDate, timezone: Option<&str>) -> Result<DateTime<Utc>, ScheduleError> {
+    let midnight = day.and_time(NaiveTime::MIN);
+    ScheduleTime::Local(midnight)
+        .resolve(timezone)
+        .or_else(|_| ScheduleTime::Local(midnight + Duration::hours(1)).resolve(timezone))
+}
+
+/// Invalid publishing schedule
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ScheduleError {
+    #[error("'{0}' is not a date and time")]
+    InvalidTime(String),
+    #[error("Unknown timezone '{0}'")]
+    UnknownTimezone(String),
+    #[error("{0} does not exist in {1}; clocks skip it")]
+    NonexistentLocalTime(NaiveDateTime, String),
+    #[error("The expiry date must be after the publish date")]
+    ExpiresBeforePublish,
+    #[error("The embargo must end after it starts")]
+    EmptyEmbargo,
+    #[error("Scheduled posts can only move to a future date; publish the post instead")]
+    ScheduledInPast,
+    #[error("Published posts can't move to a future date; schedule the post instead")]
+    PublishedInFuture,
+    #[error("The post is embargoed until {0}")]
+    Embargoed(DateTime<Utc>),
+    #[error("Posts in the trash can't be rescheduled")]
+    Trashed,
+}
+
 /// Publish status
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -186,6 +539,19 @@ impl Default for PublishStatus {
 }
 
 impl PublishStatus {
+    /// Stored and serialized name
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Draft => "draft",
+            Self::AutoDraft => "autodraft",
+            Self::Pending => "pending",
+            Self::Private => "private",
+            Self::Published => "published",
+            Self::Scheduled => "scheduled",
+            Self::Trash => "trash",
+        }
+    }
+
     pub fn display_name(&self) -> &'static str {
         match self {
             Self::Draft => "Draft",
@@ -211,6 +577,25 @@ impl PublishStatus {
     }
 }
 
+impl FromStr for PublishStatus {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        [
+            Self::Draft,
+            Self::AutoDraft,
+            Self::Pending,
+            Self::Private,
+            Self::Published,
+            Self::Scheduled,
+            Self::Trash,
+        ]
+        .into_iter()
+        .find(|status| status.as_str() == value)
+        .ok_or_else(|| format!("Invalid publish status: {}", value))
+    }
+}
+
 /// Post visibility
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -305,4 +690,201 @@ pub enum PublishEventType {
     Restored,
     VisibilityChanged,
     Expired,
+    Embargoed,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn at(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_schedule_time_in_timezone() {
+        let summer: ScheduleTime = "2025-07-01T09:00".parse().unwrap();
+        assert_eq!(
+            summer.resolve(Some("Europe/Berlin")).unwrap(),
+            at("2025-07-01T07:00:00Z")
+        );
+        assert_eq!(summer.resolve(None).unwrap(), at("2025-07-01T09:00:00Z"));
+
+        let absolute: ScheduleTime = "2025-07-01T09:00:00+02:00".parse().unwrap();
+        assert_eq!(
+            absolute.resolve(Some("America/New_York")).unwrap(),
+            at("2025-07-01T07:00:00Z")
+        );
+
+        // Clocks go forward at 02:00 and back at 03:00
+        let skipped: ScheduleTime = "2025-03-30T02:30".parse().unwrap();
+        assert!(matches!(
+            skipped.resolve(Some("Europe/Berlin")),
+            Err(ScheduleError::NonexistentLocalTime(..))
+        ));
+        let repeated: ScheduleTime = "2025-10-26T02:30".parse().unwrap();
+        assert_eq!(
+            repeated.resolve(Some("Europe/Berlin")).unwrap(),
+            at("2025-10-26T00:30:00Z")
+        );
+
+        assert!(summer.resolve(Some("Mars/Olympus")).is_err());
+        assert!("next tuesday".parse::<ScheduleTime>().is_err());
+        let json = serde_json::to_string(&summer).unwrap();
+        assert_eq!(json, "\"2025-07-01T09:00:00\"");
+        assert_eq!(serde_json::from_str::<ScheduleTime>(&json).unwrap(), summer);
+    }
+
+    #[test]
+    fn test_embargo_moves_publish_date() {
+        let now = at("2025-05-01T12:00:00Z");
+        let mut publishing = PostPublishing::default();
+        publishing
+            .set_embargo(Some(
+                EmbargoWindow::new(None, now + Duration::hours(2)).unwrap(),
+            ))
+            .unwrap();
+
+        publishing.publish_at(now, now);
+        assert_eq!(publishing.status, PublishStatus::Scheduled);
+        assert_eq!(publishing.scheduled_at, Some(now + Duration::hours(2)));
+        assert_eq!(publishing.apply_due(now + Duration::hours(1)), None);
+        assert_eq!(
+            publishing.apply_due(now + Duration::hours(2)),
+            Some(PublishEventType::Published)
+        );
+        assert_eq!(publishing.published_at, Some(now + Duration::hours(2)));
+
+        assert!(EmbargoWindow::new(Some(now), now).is_err());
+    }
+
+    #[test]
+    fn test_due_transitions() {
+        let now = at("2025-05-01T12:00:00Z");
+        let mut publishing = PostPublishing::default();
+        publishing.publish_at(now - Duration::days(1), now);
+        assert_eq!(publishing.status, PublishStatus::Published);
+        assert_eq!(publishing.published_at, Some(now - Duration::days(1)));
+
+        // An embargo starting later withholds the post once it starts
+        let embargo =
+            EmbargoWindow::new(Some(now + Duration::hours(1)), now + Duration::hours(3)).unwrap();
+        publishing.set_embargo(Some(embargo)).unwrap();
+        assert_eq!(publishing.apply_due(now), None);
+        assert_eq!(
+            publishing.apply_due(now + Duration::hours(1)),
+            Some(PublishEventType::Embargoed)
+        );
+        assert_eq!(publishing.status, PublishStatus::Scheduled);
+        assert_eq!(
+            publishing.apply_due(now + Duration::hours(3)),
+            Some(PublishEventType::Published)
+        );
+        assert_eq!(publishing.published_at, Some(now - Duration::days(1)));
+
+        publishing
+            .set_expiry(Some(now + Duration::hours(4)))
+            .unwrap();
+        assert_eq!(
+            publishing.apply_due(now + Duration::hours(4)),
+            Some(PublishEventType::Expired)
+        );
+        assert_eq!(publishing.status, PublishStatus::Draft);
+        assert_eq!(publishing.expires_at, None);
+    }
+
+    #[test]
+    fn test_expiry_after_going_live() {
+        let now = at("2025-05-01T12:00:00Z");
+        let mut publishing = PostPublishing::default();
+        publishing.publish_at(now + Duration::days(1), now);
+        assert_eq!(
+            publishing.set_expiry(Some(now + Duration::hours(1))),
+            Err(ScheduleError::ExpiresBeforePublish)
+        );
+        publishing
+            .set_expiry(Some(now + Duration::days(2)))
+            .unwrap();
+
+        // An embargo can't push the publish date past the expiry
+        let embargo = EmbargoWindow::new(None, now + Duration::days(3)).unwrap();
+        assert_eq!(
+            publishing.set_embargo(Some(embargo)),
+            Err(ScheduleError::ExpiresBeforePublish)
+        );
+    }
+
+    #[test]
+    fn test_calendar_moves() {
+        let now = at("2025-05-01T12:00:00Z");
+
+        // Drafts get a planned date and stay drafts
+        let mut draft = PostPublishing::default();
+        draft.move_to(now - Duration::days(2), now).unwrap();
+        assert_eq!(draft.status, PublishStatus::Draft);
+        assert_eq!(draft.calendar_date(), Some(now - Duration::days(2)));
+
+        let mut scheduled = PostPublishing::default();
+        scheduled.publish_at(now + Duration::days(1), now);
+        assert_eq!(
+            scheduled.move_to(now - Duration::hours(1), now),
+            Err(ScheduleError::ScheduledInPast)
+        );
+        let embargo = EmbargoWindow::new(None, now + Duration::days(5)).unwrap();
+        scheduled.embargo = Some(embargo);
+        assert_eq!(
+            scheduled.move_to(now + Duration::days(4), now),
+            Err(ScheduleError::Embargoed(embargo.ends_at))
+        );
+        scheduled.move_to(now + Duration::days(6), now).unwrap();
+        assert_eq!(scheduled.status, PublishStatus::Scheduled);
+        assert_eq!(scheduled.calendar_date(), Some(now + Duration::days(6)));
+
+        let mut published = PostPublishing::default();
+        published.publish_at(now, now);
+        assert_eq!(
+            published.move_to(now + Duration::days(1), now),
+            Err(ScheduleError::PublishedInFuture)
+        );
+        published.move_to(now - Duration::days(7), now).unwrap();
+        assert_eq!(published.calendar_date(), Some(now - Duration::days(7)));
+
+        // Dragging to another day keeps the time of day in the post's timezone
+        published.set_timezone(Some("Europe/Berlin")).unwrap();
+        let day = NaiveDate::from_ymd_opt(2025, 6, 1).unwrap();
+        assert_eq!(
+            published.same_time_on(day).unwrap(),
+            at("2025-06-01T12:00:00Z")
+        );
+        assert_eq!(draft.same_time_on(day).unwrap(), at("2025-06-01T12:00:00Z"));
+        assert_eq!(
+            PostPublishing::default().same_time_on(day).unwrap(),
+            at("2025-06-01T09:00:00Z")
+        );
+        assert_eq!(
+            day_start(day, Some("Europe/Berlin")).unwrap(),
+            at("2025-05-31T22:00:00Z")
+        );
+        assert_eq!(
+            local_date(at("2025-05-31T22:30:00Z"), Some("Europe/Berlin")).unwrap(),
+            day
+        );
+
+        published.status = PublishStatus::Trash;
+        assert_eq!(published.calendar_date(), None);
+        assert_eq!(published.move_to(now, now), Err(ScheduleError::Trashed));
+    }
+
+    #[test]
+    fn test_status_names() {
+        for status in ["draft", "autodraft", "scheduled", "trash"] {
+            let parsed: PublishStatus = status.parse().unwrap();
+            assert_eq!(parsed.as_str(), status);
+            assert_eq!(serde_json::to_value(parsed).unwrap(), status);
+        }
+        assert!("future".parse::<PublishStatus>().is_err());
+    }
 }
@@ -1865,3 +1865,175 @@ fn test_165_css_classes() {
     assert!(html.contains("custom-class"));
     assert!(html.contains("another-class"));
 }
+
+// ============================================================================
+// MARKDOWN ROUND-TRIP TESTS (166-170)
+// ============================================================================
+
+const GFM_DOCUMENT: &str = r#"# Release notes {#notes}
+
+Some **bold**, *emphasis*, ~~struck~~ and `code` with a [link](https://example.com "Example").[^1]
+
+- [x] Ship it
+- [ ] Announce it
+  1. Blog
+  2. Newsletter
+
+> Quoted text
+
+| Name | Count |
+| :--- | ---: |
+| Apples | 3 |
+| Pears \| plums | 5 |
+
+```rust
+fn main() {}
+```
+
+![Chart](https://example.com/chart.png)
+
+---
+
+[^1]: The footnote."#;
+
+#[test]
+fn test_166_markdown_parses_gfm_into_blocks() {
+    let serializer = BlockSerializer::new();
+    let blocks = serializer.from_markdown(GFM_DOCUMENT);
+
+    let types: Vec<BlockType> = blocks.iter().map(|b| b.block_type).collect();
+    assert_eq!(
+        types,
+        vec![
+            BlockType::Heading,
+            BlockType::Paragraph,
+            BlockType::List,
+            BlockType::Quote,
+            BlockType::Table,
+            BlockType::Code,
+            BlockType::Image,
+            BlockType::Separator,
+            BlockType::Footnotes,
+        ]
+    );
+
+    assert_eq!(blocks[0].meta.anchor.as_deref(), Some("notes"));
+    let paragraph = blocks[1].attributes.content.as_deref().unwrap();
+    assert!(paragraph.contains("<strong>bold</strong>"));
+    assert!(paragraph.contains("<del>struck</del>"));
+    assert!(paragraph.contains(r#"<a href="https://example.com" title="Example">link</a>"#));
+    assert!(paragraph.contains(r##"<a href="#fn-1">1</a>"##));
+
+    let list = &blocks[2];
+    assert_eq!(list.attributes.list_type, Some(ListType::Checklist));
+    assert_eq!(list.children[0].attributes.custom["checked"], true);
+    assert_eq!(list.children[1].attributes.custom["checked"], false);
+    assert_eq!(
+        list.children[1].children[0].attributes.list_type,
+        Some(ListType::Ordered)
+    );
+
+    let table = blocks[4].attributes.table_data.as_ref().unwrap();
+    assert_eq!(table.headers, vec!["Name", "Count"]);
+    assert_eq!(table.rows[1][0], "Pears | plums");
+
+    assert_eq!(blocks[5].attributes.language.as_deref(), Some("rust"));
+    assert_eq!(
+        blocks[5].attributes.content.as_deref(),
+        Some("fn main() {}")
+    );
+    assert_eq!(
+        blocks[8].children[0].attributes.content.as_deref(),
+        Some("The footnote.")
+    );
+}
+
+#[test]
+fn test_167_markdown_round_trip_is_stable() {
+    let serializer = BlockSerializer::new();
+    let blocks = serializer.from_markdown(GFM_DOCUMENT);
+
+    let markdown = serializer.to_markdown(&blocks);
+    assert!(!markdown.contains("rustpress:block"), "{}", markdown);
+    assert_eq!(markdown, GFM_DOCUMENT);
+    assert_eq!(
+        serializer.to_markdown(&serializer.from_markdown(&markdown)),
+        markdown
+    );
+}
+
+#[test]
+fn test_168_markdown_keeps_unknown_blocks_as_comments() {
+    let serializer = BlockSerializer::new();
+
+    let mut columns = Block::new(BlockType::Columns);
+    let mut column = Block::new(BlockType::Column);
+    let mut text = Block::new(BlockType::Paragraph);
+    text.attributes.content = Some("Inside --> a column".to_string());
+    column.children.push(text);
+    columns.children.push(column);
+
+    let mut styled = Block::new(BlockType::Paragraph);
+    styled.attributes.content = Some("Red text".to_string());
+    styled.styles.text_color = Some("red".to_string());
+
+    let mut plain = Block::new(BlockType::Paragraph);
+    plain.attributes.content = Some("Plain text".to_string());
+
+    let markdown = serializer.to_markdown(&[columns.clone(), styled, plain]);
+    assert!(markdown.starts_with("<!-- rustpress:block "));
+    assert!(markdown.ends_with("\n\nPlain text"));
+    assert_eq!(markdown.matches("-->").count(), 2);
+
+    let blocks = serializer.from_markdown(&markdown);
+    assert_eq!(blocks.len(), 3);
+    assert_eq!(blocks[0].id, columns.id);
+    assert_eq!(
+        blocks[0].children[0].children[0]
+            .attributes
+            .content
+            .as_deref(),
+        Some("Inside --> a column")
+    );
+    assert_eq!(blocks[1].styles.text_color.as_deref(), Some("red"));
+    assert_eq!(blocks[2].attributes.content.as_deref(), Some("Plain text"));
+}
+
+#[test]
+fn test_169_markdown_escapes_literal_syntax() {
+    let serializer = BlockSerializer::new();
+
+    let mut paragraph = Block::new(BlockType::Paragraph);
+    paragraph.attributes.content =
+        Some("1. Not a list, *not emphasis*, snake_case &amp; a &lt;tag&gt;".to_string());
+    let mut heading = Block::new(BlockType::Heading);
+    heading.attributes.content = Some("# Not nested".to_string());
+
+    let markdown = serializer.to_markdown(&[paragraph.clone(), heading.clone()]);
+    assert!(markdown.starts_with(r"1\. Not a list, \*not emphasis\*, snake_case & a \<tag\>"));
+
+    let blocks = serializer.from_markdown(&markdown);
+    assert_eq!(blocks[0].attributes.content, paragraph.attributes.content);
+    assert_eq!(blocks[1].attributes.content, heading.attributes.content);
+}
+
+#[test]
+fn test_170_markdown_keeps_adjacent_lists_apart() {
+    let serializer = BlockSerializer::new();
+
+    let list = |text: &str| {
+        let mut item = Block::new(BlockType::ListItem);
+        item.attributes.content = Some(text.to_string());
+        let mut list = Block::new(BlockType::List);
+        list.children.push(item);
+        list
+    };
+
+    let markdown = serializer.to_markdown(&[list("First"), list("Second")]);
+    let blocks = serializer.from_markdown(&markdown);
+    assert_eq!(blocks.len(), 2);
+    assert_eq!(
+        blocks[1].children[0].attributes.content.as_deref(),
+        Some("Second")
+    );
+}
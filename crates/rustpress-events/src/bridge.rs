@@ -0,0 +1,560 @@
+//! Bridges between the event bus and external message brokers.
+//!
+//! An [`EventBridge`] publishes domain events from the local [`EventBus`]
+//! to a [`Broker`], so pipelines such as search indexing or a data
+//! warehouse can follow the site without polling the REST API. It can also
+//! consume commands from the broker and publish them on the bus, where
+//! subscribers handle them like any other event.
+//!
+//! [`KafkaBroker`] (feature `kafka`) writes events to the topic
+//! `{prefix}.events` keyed by aggregate and reads commands from
+//! `{prefix}.commands` through a consumer group. [`NatsBroker`] (feature
+//! `nats`) uses JetStream: events go to `{prefix}.events.{event_type}` and
+//! commands are read from `{prefix}.commands.>` through a durable consumer.
+//! Either way each command is handled by one node, and acknowledged once
+//! it was published on that node's bus.
+//!
+//! `change.*` events are never exported: every node republishes them, so
+//! they would reach the broker once per node.
+
+use crate::bus::EventBus;
+use crate::event::DomainEvent;
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use rustpress_core::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+/// Metadata key marking events that came in through a bridge
+pub const BRIDGE_SOURCE_KEY: &str = "bridge";
+
+/// Wait before consuming commands again after the connection failed
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Called with the body of each command; the broker acknowledges the
+/// command once the returned future completes
+pub type CommandHandler = Arc<dyn Fn(Vec<u8>) -> BoxFuture<'static, ()> + Send + Sync>;
+
+/// External message broker behind an [`EventBridge`]
+#[async_trait]
+pub trait Broker: Send + Sync {
+    /// Broker name, for logs and event metadata
+    fn name(&self) -> &'static str;
+
+    /// Publish a serialized event; `key` keeps events of one aggregate in
+    /// order where the broker partitions
+    async fn publish(&self, event_type: &str, key: &str, payload: Vec<u8>) -> Result<()>;
+
+    /// Receive commands until the connection fails, passing each to
+    /// `handler`
+    async fn consume(&self, handler: CommandHandler) -> Result<()>;
+}
+
+/// Which events leave the bus and which commands come back
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BridgeConfig {
+    /// Prefix of topic and subject names
+    pub prefix: String,
+    /// Event types to publish: exact names, `post.*` or `*`
+    pub events: Vec<String>,
+    /// Command types accepted from the broker; none when empty
+    pub commands: Vec<String>,
+}
+
+impl Default for BridgeConfig {
+    fn default() -> Self {
+        Self {
+            prefix: "rustpress".to_string(),
+            events: vec!["*".to_string()],
+            commands: Vec::new(),
+        }
+    }
+}
+
+impl BridgeConfig {
+    /// Whether an event is published to the broker
+    pub fn exports(&self, event: &DomainEvent) -> bool {
+        !event.event_type.starts_with("change.")
+            && !event.metadata.data.contains_key(BRIDGE_SOURCE_KEY)
+            && self
+                .events
+                .iter()
+                .any(|pattern| type_matches(pattern, &event.event_type))
+    }
+
+    /// Whether a command type is accepted from the broker
+    pub fn accepts(&self, command_type: &str) -> bool {
+        self.commands
+            .iter()
+            .any(|pattern| type_matches(pattern, command_type))
+    }
+}
+
+/// Match an event type against `*`, `prefix.*` or an exact name
+fn type_matches(pattern: &str, event_type: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => event_type.starts_with(prefix),
+        None => pattern == event_type,
+    }
+}
+
+/// A command as external producers send it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BrokerCommand {
+    /// Event type the command is published as
+    pub event_type: String,
+    #[serde(default)]
+    pub payload: serde_json::Value,
+    #[serde(default)]
+    pub aggregate_id: Option<Uuid>,
+    #[serde(default)]
+    pub aggregate_type: Option<String>,
+    #[serde(default)]
+    pub tenant_id: Option<Uuid>,
+    #[serde(default)]
+    pub correlation_id: Option<Uuid>,
+}
+
+impl BrokerCommand {
+    /// The domain event published on the bus for this command
+    fn into_event(self, broker: &str) -> DomainEvent {
+        let mut event = DomainEvent::new(self.event_type, self.payload)
+            .with_metadata(BRIDGE_SOURCE_KEY, serde_json::json!(broker));
+        event.aggregate_id = self.aggregate_id;
+        event.aggregate_type = self.aggregate_type;
+        event.tenant_id = self.tenant_id;
+        event.metadata.correlation_id = self.correlation_id;
+        event
+    }
+}
+
+/// Publishes bus events to a broker and commands from it on the bus
+pub struct EventBridge {
+    broker: Arc<dyn Broker>,
+    config: BridgeConfig,
+}
+
+impl EventBridge {
+    pub fn new(broker: Arc<dyn Broker>, config: BridgeConfig) -> Self {
+        Self { broker, config }
+    }
+
+    pub fn broker_name(&self) -> &'static str {
+        self.broker.name()
+    }
+
+    /// Start publishing events and, when commands are configured,
+    /// consuming them
+    pub fn spawn(self: &Arc<Self>, bus: &Arc<EventBus>) {
+        let bridge = self.clone();
+        let mut events = bus.subscribe_broadcast();
+        tokio::spawn(async move {
+            loop {
+                match events.recv().await {
+                    Ok(event) => bridge.export(&event).await,
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        tracing::warn!(
+                            broker = bridge.broker_name(),
+                            missed,
+                            "Event bridge fell behind the event bus, events skipped"
+                        );
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+
+        if self.config.commands.is_empty() {
+            return;
+        }
+        let bridge = self.clone();
+        let bus = bus.clone();
+        tokio::spawn(async move {
+            let handler: CommandHandler = {
+                let bridge = bridge.clone();
+                Arc::new(move |body| {
+                    let bridge = bridge.clone();
+                    let bus = bus.clone();
+                    Box::pin(async move {
+                        if let Err(e) = bridge.handle_command(&bus, &body).await {
+                            tracing::warn!(
+                                broker = bridge.broker_name(),
+                                "Rejected command from the broker: {}",
+                                e
+                            );
+                        }
+                    })
+                })
+            };
+            loop {
+                if let Err(e) = bridge.broker.consume(handler.clone()).await {
+                    tracing::error!(
+                        broker = bridge.broker_name(),
+                        "Consuming commands failed: {}",
+                        e
+                    );
+                }
+                tokio::time::sleep(RECONNECT_DELAY).await;
+            }
+        });
+    }
+
+    /// Publish one event if the configuration exports it
+    async fn export(&self, event: &DomainEvent) {
+        if !self.config.exports(event) {
+            return;
+        }
+        let payload = match serde_json::to_vec(event) {
+            Ok(payload) => payload,
+            Err(e) => {
+                tracing::error!(event_type = %event.event_type, "Failed to serialize event: {}", e);
+                return;
+            }
+        };
+        let key = event.aggregate_id.unwrap_or(event.id).to_string();
+        if let Err(e) = self.broker.publish(&event.event_type, &key, payload).await {
+            tracing::error!(
+                broker = self.broker_name(),
+                event_type = %event.event_type,
+                event_id = %event.id,
+                "Failed to publish event to the broker: {}",
+                e
+            );
+        }
+    }
+
+    /// Publish an accepted command on the bus
+    async fn handle_command(&self, bus: &EventBus, body: &[u8]) -> Result<()> {
+        let command: BrokerCommand = serde_json::from_slice(body)
+            .map_err(|e| Error::serialization_with_source("Invalid command", e))?;
+        if !self.config.accepts(&command.event_type) {
+            return Err(Error::invalid_input(
+                "event_type",
+                format!("Command '{}' is not accepted", command.event_type),
+            ));
+        }
+        tracing::debug!(
+            broker = self.broker_name(),
+            event_type = %command.event_type,
+            "Publishing command from the broker"
+        );
+        bus.publish(command.into_event(self.broker_name())).await
+    }
+}
+
+#[cfg(any(feature = "kafka", feature = "nats"))]
+fn broker_error(
+    broker: &str,
+    message: &str,
+    source: impl std::error::Error + Send + Sync + 'static,
+) -> Error {
+    Error::Network {
+        message: format!("{}: {}", broker, message),
+        source: Some(Box::new(source)),
+    }
+}
+
+// =============================================================================
+// Kafka
+// =============================================================================
+
+/// Events in one Kafka topic, keyed by aggregate and carrying their type in
+/// the `event-type` header; commands from another topic
+#[cfg(feature = "kafka")]
+pub struct KafkaBroker {
+    producer: rdkafka::producer::FutureProducer,
+    brokers: String,
+    group_id: String,
+    events_topic: String,
+    commands_topic: String,
+}
+
+#[cfg(feature = "kafka")]
+impl KafkaBroker {
+    /// Connect to the comma-separated `brokers`; nodes of one site share
+    /// the consumer group `{prefix}-bridge`
+    pub fn new(brokers: &str, prefix: &str) -> Result<Self> {
+        let producer = rdkafka::ClientConfig::new()
+            .set("bootstrap.servers", brokers)
+            .set("message.timeout.ms", "30000")
+            .create()
+            .map_err(|e| broker_error("Kafka", "failed to create producer", e))?;
+        Ok(Self {
+            producer,
+            brokers: brokers.to_string(),
+            group_id: format!("{}-bridge", prefix),
+            events_topic: format!("{}.events", prefix),
+            commands_topic: format!("{}.commands", prefix),
+        })
+    }
+}
+
+#[cfg(feature = "kafka")]
+#[async_trait]
+impl Broker for KafkaBroker {
+    fn name(&self) -> &'static str {
+        "kafka"
+    }
+
+    async fn publish(&self, event_type: &str, key: &str, payload: Vec<u8>) -> Result<()> {
+        use rdkafka::message::{Header, OwnedHeaders};
+        use rdkafka::producer::FutureRecord;
+
+        let record = FutureRecord::to(&self.events_topic)
+            .key(key)
+            .payload(&payload)
+            .headers(OwnedHeaders::new().insert(Header {
+                key: "event-type",
+                value: Some(event_type),
+            }));
+        self.producer
+            .send(record, Duration::from_secs(30))
+            .await
+            .map_err(|(e, _)| broker_error("Kafka", "delivery failed", e))?;
+        Ok(())
+    }
+
+    async fn consume(&self, handler: CommandHandler) -> Result<()> {
+        use rdkafka::consumer::{Consumer, StreamConsumer};
+        use rdkafka::Message;
+
+        // Offsets are stored once a command was handled and committed in
+        // the background
+        let consumer: StreamConsumer = rdkafka::ClientConfig::new()
+            .set("bootstrap.servers", &self.brokers)
+            .set("group.id", &self.group_id)
+            .set("enable.auto.commit", "true")
+            .set("enable.auto.offset.store", "false")
+            .create()
+            .map_err(|e| broker_error("Kafka", "failed to create consumer", e))?;
+        consumer
+            .subscribe(&[&self.commands_topic])
+            .map_err(|e| broker_error("Kafka", "failed to subscribe to commands", e))?;
+
+        loop {
+            let message = consumer
+                .recv()
+                .await
+                .map_err(|e| broker_error("Kafka", "failed to receive command", e))?;
+            if let Some(body) = message.payload() {
+                handler(body.to_vec()).await;
+            }
+            consumer
+                .store_offset_from_message(&message)
+                .map_err(|e| broker_error("Kafka", "failed to store offset", e))?;
+        }
+    }
+}
+
+// =============================================================================
+// NATS JetStream
+// =============================================================================
+
+/// Events and commands in two JetStream streams, created on connect
+#[cfg(feature = "nats")]
+pub struct NatsBroker {
+    jetstream: async_nats::jetstream::Context,
+    prefix: String,
+    commands_stream: String,
+}
+
+#[cfg(feature = "nats")]
+impl NatsBroker {
+    /// Connect to `url` and create the streams `{PREFIX}_EVENTS` and
+    /// `{PREFIX}_COMMANDS` if they do not exist
+    pub async fn connect(url: &str, prefix: &str) -> Result<Self> {
+        use async_nats::jetstream::stream::Config;
+
+        let client = async_nats::connect(url)
+            .await
+            .map_err(|e| broker_error("NATS", "failed to connect", e))?;
+        let jetstream = async_nats::jetstream::new(client);
+
+        let stream_prefix = prefix.replace(['.', '-'], "_").to_uppercase();
+        let commands_stream = format!("{}_COMMANDS", stream_prefix);
+        for (name, subjects) in [
+            (
+                format!("{}_EVENTS", stream_prefix),
+                format!("{}.events.>", prefix),
+            ),
+            (commands_stream.clone(), format!("{}.commands.>", prefix)),
+        ] {
+            jetstream
+                .get_or_create_stream(Config {
+                    name,
+                    subjects: vec![subjects],
+                    ..Default::default()
+                })
+                .await
+                .map_err(|e| broker_error("NATS", "failed to create stream", e))?;
+        }
+
+        Ok(Self {
+            jetstream,
+            prefix: prefix.to_string(),
+            commands_stream,
+        })
+    }
+}
+
+#[cfg(feature = "nats")]
+#[async_trait]
+impl Broker for NatsBroker {
+    fn name(&self) -> &'static str {
+        "nats"
+    }
+
+    async fn publish(&self, event_type: &str, _key: &str, payload: Vec<u8>) -> Result<()> {
+        let subject = format!("{}.events.{}", self.prefix, event_type);
+        let ack = self
+            .jetstream
+            .publish(subject, payload.into())
+            .await
+            .map_err(|e| broker_error("NATS", "publish failed", e))?;
+        ack.await
+            .map_err(|e| broker_error("NATS", "publish was not acknowledged", e))?;
+        Ok(())
+    }
+
+    async fn consume(&self, handler: CommandHandler) -> Result<()> {
+        use async_nats::jetstream::consumer::pull::Config;
+        use futures::StreamExt;
+
+        let stream = self
+            .jetstream
+            .get_stream(&self.commands_stream)
+            .await
+            .map_err(|e| broker_error("NATS", "failed to open command stream", e))?;
+        let consumer = stream
+            .get_or_create_consumer(
+                "bridge",
+                Config {
+                    durable_name: Some("bridge".to_string()),
+                    ..Default::default()
+                },
+            )
+            .await
+            .map_err(|e| broker_error("NATS", "failed to create command consumer", e))?;
+        let mut messages = consumer
+            .messages()
+            .await
+            .map_err(|e| broker_error("NATS", "failed to read commands", e))?;
+
+        while let Some(message) = messages.next().await {
+            let message =
+                message.map_err(|e| broker_error("NATS", "failed to receive command", e))?;
+            handler(message.payload.to_vec()).await;
+            if let Err(e) = message.ack().await {
+                tracing::warn!("Failed to acknowledge NATS command: {}", e);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::subscriber::Subscriber;
+    use parking_lot::Mutex;
+
+    /// Records published events and hands out queued commands
+    #[derive(Default)]
+    struct TestBroker {
+        published: Mutex<Vec<(String, String)>>,
+        commands: Mutex<Vec<Vec<u8>>>,
+    }
+
+    #[async_trait]
+    impl Broker for TestBroker {
+        fn name(&self) -> &'static str {
+            "test"
+        }
+
+        async fn publish(&self, event_type: &str, key: &str, _payload: Vec<u8>) -> Result<()> {
+            self.published
+                .lock()
+                .push((event_type.to_string(), key.to_string()));
+            Ok(())
+        }
+
+        async fn consume(&self, handler: CommandHandler) -> Result<()> {
+            let commands = std::mem::take(&mut *self.commands.lock());
+            for body in commands {
+                handler(body).await;
+            }
+            std::future::pending().await
+        }
+    }
+
+    #[test]
+    fn test_exports_and_accepts() {
+        let config = BridgeConfig {
+            events: vec!["post.*".to_string(), "user.created".to_string()],
+            commands: vec!["search.*".to_string()],
+            ..Default::default()
+        };
+        let event = |event_type: &str| DomainEvent::new(event_type, serde_json::json!({}));
+
+        assert!(config.exports(&event("post.published")));
+        assert!(config.exports(&event("user.created")));
+        assert!(!config.exports(&event("user.deleted")));
+        assert!(!config.exports(
+            &event("post.published").with_metadata(BRIDGE_SOURCE_KEY, serde_json::json!("nats"))
+        ));
+        assert!(!BridgeConfig::default().exports(&event("change.post.updated")));
+
+        assert!(config.accepts("search.reindex"));
+        assert!(!config.accepts("post.delete"));
+        assert!(!BridgeConfig::default().accepts("search.reindex"));
+    }
+
+    #[tokio::test]
+    async fn test_bridge_publishes_events_and_commands() {
+        let bus = Arc::new(EventBus::new());
+        let broker = Arc::new(TestBroker::default());
+        broker.commands.lock().extend([
+            br#"{"event_type":"search.reindex","payload":{"site":"a"}}"#.to_vec(),
+            br#"{"event_type":"post.delete","payload":{}}"#.to_vec(),
+            b"not json".to_vec(),
+        ]);
+
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let seen = received.clone();
+        bus.subscribe(Subscriber::for_event("search.reindex", move |event| {
+            let seen = seen.clone();
+            async move {
+                seen.lock().push(event.payload.clone());
+                Ok(())
+            }
+        }));
+
+        let config = BridgeConfig {
+            commands: vec!["search.*".to_string()],
+            ..Default::default()
+        };
+        let bridge = Arc::new(EventBridge::new(broker.clone(), config));
+        bridge.spawn(&bus);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let aggregate = Uuid::new_v4();
+        bus.publish(
+            DomainEvent::new("post.published", serde_json::json!({}))
+                .with_aggregate(aggregate, "post"),
+        )
+        .await
+        .unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        assert_eq!(*received.lock(), vec![serde_json::json!({"site": "a"})]);
+        // The command is not echoed back to the broker
+        assert_eq!(
+            *broker.published.lock(),
+            vec![("post.published".to_string(), aggregate.to_string())]
+        );
+    }
+}
@@ -1,6 +1,7 @@
 //! Event bus for publishing and subscribing to events.
 
 use crate::event::{DomainEvent, EventType};
+use crate::schema::SchemaRegistry;
 use crate::subscriber::Subscriber;
 use dashmap::DashMap;
 use parking_lot::RwLock;
@@ -16,6 +17,8 @@ pub struct EventBus {
     broadcast_tx: broadcast::Sender<Arc<DomainEvent>>,
     /// Event history for replay (optional)
     history: Option<RwLock<Vec<Arc<DomainEvent>>>>,
+    /// Payload schemas for validation and upcasting
+    schemas: Option<Arc<SchemaRegistry>>,
     /// Configuration
     config: EventBusConfig,
 }
@@ -31,6 +34,9 @@ pub struct EventBusConfig {
     pub broadcast_capacity: usize,
     /// Continue on handler error
     pub continue_on_error: bool,
+    /// Reject events whose payload does not match the registered schema
+    /// (enabled by default in debug builds)
+    pub validate_schemas: bool,
 }
 
 impl Default for EventBusConfig {
@@ -40,6 +46,7 @@ impl Default for EventBusConfig {
             enable_history: false,
             broadcast_capacity: 1024,
             continue_on_error: true,
+            validate_schemas: cfg!(debug_assertions),
         }
     }
 }
@@ -63,10 +70,22 @@ impl EventBus {
             subscribers: DashMap::new(),
             broadcast_tx,
             history,
+            schemas: None,
             config,
         }
     }
 
+    /// Attach a schema registry used for payload validation and upcasting
+    pub fn with_schema_registry(mut self, schemas: Arc<SchemaRegistry>) -> Self {
+        self.schemas = Some(schemas);
+        self
+    }
+
+    /// Get the schema registry (if configured)
+    pub fn schema_registry(&self) -> Option<&Arc<SchemaRegistry>> {
+        self.schemas.as_ref()
+    }
+
     /// Subscribe to events
     pub fn subscribe(&self, subscriber: Subscriber) -> &Self {
         let subscriber = Arc::new(subscriber);
@@ -97,6 +116,20 @@ impl EventBus {
 
     /// Publish an event
     pub async fn publish(&self, event: DomainEvent) -> Result<()> {
+        if self.config.validate_schemas {
+            if let Some(schemas) = &self.schemas {
+                if let Err(e) = schemas.validate(&event) {
+                    tracing::error!(
+                        event_type = %event.event_type,
+                        schema_version = ?event.schema_version,
+                        error = %e,
+                        "Event payload does not match registered schema"
+                    );
+                    return Err(e);
+                }
+            }
+        }
+
         let event = Arc::new(event);
 
         tracing::debug!(
@@ -125,7 +158,11 @@ impl EventBus {
         // Sync subscribers
         let mut errors = Vec::new();
         for subscriber in subscribers.iter().filter(|s| !s.config.async_handler) {
-            if let Err(e) = subscriber.handle(event.clone()).await {
+            let result = match adapt_event(self.schemas.as_deref(), subscriber, &event) {
+                Ok(adapted) => subscriber.handle(adapted).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
                 tracing::error!(
                     subscriber = %subscriber.name,
                     event_type = %event.event_type,
@@ -148,9 +185,14 @@ impl EventBus {
 
         if !async_subscribers.is_empty() {
             let event_clone = event.clone();
+            let schemas = self.schemas.clone();
             tokio::spawn(async move {
                 for subscriber in async_subscribers {
-                    if let Err(e) = subscriber.handle(event_clone.clone()).await {
+                    let result = match adapt_event(schemas.as_deref(), &subscriber, &event_clone) {
+                        Ok(adapted) => subscriber.handle(adapted).await,
+                        Err(e) => Err(e),
+                    };
+                    if let Err(e) = result {
                         tracing::error!(
                             subscriber = %subscriber.name,
                             error = %e,
@@ -228,9 +270,24 @@ impl Default for EventBus {
     }
 }
 
+/// Upcast an event to the payload version a subscriber expects
+fn adapt_event(
+    schemas: Option<&SchemaRegistry>,
+    subscriber: &Subscriber,
+    event: &Arc<DomainEvent>,
+) -> Result<Arc<DomainEvent>> {
+    match (schemas, subscriber.config.schema_version) {
+        (Some(schemas), Some(version)) if schemas.event_version(event) < Some(version) => {
+            Ok(Arc::new(schemas.upcast(event, version)?))
+        }
+        _ => Ok(event.clone()),
+    }
+}
+
 /// Builder for EventBus
 pub struct EventBusBuilder {
     config: EventBusConfig,
+    schemas: Option<Arc<SchemaRegistry>>,
     subscribers: Vec<Subscriber>,
 }
 
@@ -238,6 +295,7 @@ impl EventBusBuilder {
     pub fn new() -> Self {
         Self {
             config: EventBusConfig::default(),
+            schemas: None,
             subscribers: Vec::new(),
         }
     }
@@ -258,13 +316,24 @@ impl EventBusBuilder {
         self
     }
 
+    pub fn schema_registry(mut self, schemas: Arc<SchemaRegistry>) -> Self {
+        self.schemas = Some(schemas);
+        self
+    }
+
+    pub fn validate_schemas(mut self, validate: bool) -> Self {
+        self.config.validate_schemas = validate;
+        self
+    }
+
     pub fn subscriber(mut self, subscriber: Subscriber) -> Self {
         self.subscribers.push(subscriber);
         self
     }
 
     pub fn build(self) -> EventBus {
-        let bus = EventBus::with_config(self.config);
+        let mut bus = EventBus::with_config(self.config);
+        bus.schemas = self.schemas;
         for subscriber in self.subscribers {
             bus.subscribe(subscriber);
         }
@@ -385,4 +454,57 @@ mod tests {
         let received = receiver.recv().await.unwrap();
         assert_eq!(received.event_type, "test.event");
     }
+
+    #[tokio::test]
+    async fn test_schema_validation_and_upcasting() {
+        use crate::schema::EventSchema;
+        use crate::subscriber::SubscriberConfig;
+
+        let schemas = Arc::new(SchemaRegistry::new());
+        schemas
+            .register(EventSchema::new(
+                "test.event",
+                1,
+                serde_json::json!({"type": "object", "required": ["name"]}),
+            ))
+            .unwrap();
+        schemas
+            .register(EventSchema::new(
+                "test.event",
+                2,
+                serde_json::json!({"type": "object", "required": ["first_name"]}),
+            ))
+            .unwrap();
+        schemas.register_upcaster("test.event", 1, |payload| {
+            Ok(serde_json::json!({"first_name": payload["name"]}))
+        });
+
+        let bus = EventBusBuilder::new()
+            .schema_registry(schemas)
+            .validate_schemas(true)
+            .build();
+
+        let seen = Arc::new(RwLock::new(Vec::new()));
+        let seen_clone = seen.clone();
+        bus.subscribe(Subscriber::new(
+            "v2_sub",
+            SubscriberConfig::new(vec![EventType::new("test.event")]).with_schema_version(2),
+            move |event| {
+                let seen = seen_clone.clone();
+                async move {
+                    seen.write().push(event.payload["first_name"].clone());
+                    Ok(())
+                }
+            },
+        ));
+
+        let v1 = DomainEvent::new("test.event", serde_json::json!({"name": "Ada"}))
+            .with_schema_version(1);
+        bus.publish(v1).await.unwrap();
+        assert_eq!(*seen.read(), vec![serde_json::json!("Ada")]);
+
+        let invalid = DomainEvent::new("test.event", serde_json::json!({"name": "Ada"}));
+        assert!(bus.publish(invalid).await.is_err());
+        assert_eq!(seen.read().len(), 1);
+    }
 }
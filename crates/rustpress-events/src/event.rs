@@ -68,6 +68,9 @@ pub struct DomainEvent {
     pub aggregate_type: Option<String>,
     pub tenant_id: Option<Uuid>,
     pub payload: serde_json::Value,
+    /// Payload schema version, if the publisher declared one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema_version: Option<u32>,
     pub metadata: EventMetadata,
     pub occurred_at: DateTime<Utc>,
 }
@@ -81,6 +84,7 @@ impl DomainEvent {
             aggregate_type: None,
             tenant_id: None,
             payload,
+            schema_version: None,
             metadata: EventMetadata::default(),
             occurred_at: Utc::now(),
         }
@@ -97,6 +101,11 @@ impl DomainEvent {
         self
     }
 
+    pub fn with_schema_version(mut self, version: u32) -> Self {
+        self.schema_version = Some(version);
+        self
+    }
+
     pub fn with_metadata(mut self, key: impl Into<String>, value: serde_json::Value) -> Self {
         self.metadata.data.insert(key.into(), value);
         self
@@ -2,10 +2,18 @@
 //!
 //! Event bus and messaging system for decoupled component communication.
 
+pub mod bridge;
 pub mod bus;
 pub mod event;
+pub mod schema;
 pub mod subscriber;
 
+#[cfg(feature = "kafka")]
+pub use bridge::KafkaBroker;
+#[cfg(feature = "nats")]
+pub use bridge::NatsBroker;
+pub use bridge::{BridgeConfig, Broker, BrokerCommand, EventBridge};
 pub use bus::EventBus;
 pub use event::{DomainEvent, Event, EventType};
+pub use schema::{EventSchema, SchemaRegistry};
 pub use subscriber::{EventHandler, Subscriber};
@@ -0,0 +1,298 @@
+//! Versioned payload schemas for domain events.
+//!
+//! Publishers declare a JSON schema per event type and version. Subscribers
+//! that were written against an older payload shape can ask the bus to
+//! upcast events through registered migration steps, and the bus can
+//! validate payloads before dispatch while running in debug mode.
+
+use crate::event::DomainEvent;
+use dashmap::DashMap;
+use rustpress_core::error::{Error, Result, ValidationErrors};
+use rustpress_core::json_schema;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// Upcaster converting a payload from version `n` to version `n + 1`
+pub type Upcaster = Arc<dyn Fn(Value) -> Result<Value> + Send + Sync>;
+
+/// A versioned payload schema for one event type
+#[derive(Debug, Clone, Serialize)]
+pub struct EventSchema {
+    /// Event type this schema applies to
+    pub event_type: String,
+    /// Schema version (starting at 1)
+    pub version: u32,
+    /// JSON schema describing the payload
+    pub schema: Value,
+    /// Owner of the schema (core or plugin ID)
+    pub owner: Option<String>,
+    /// Human-readable description
+    pub description: Option<String>,
+}
+
+impl EventSchema {
+    pub fn new(event_type: impl Into<String>, version: u32, schema: Value) -> Self {
+        Self {
+            event_type: event_type.into(),
+            version,
+            schema,
+            owner: None,
+            description: None,
+        }
+    }
+
+    pub fn with_owner(mut self, owner: impl Into<String>) -> Self {
+        self.owner = Some(owner.into());
+        self
+    }
+
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Validate a payload against this schema
+    pub fn validate(&self, payload: &Value) -> std::result::Result<(), ValidationErrors> {
+        json_schema::validate(&self.schema, payload, "payload")
+    }
+}
+
+/// Registry of event schemas and upcasters
+#[derive(Default)]
+pub struct SchemaRegistry {
+    schemas: DashMap<String, BTreeMap<u32, EventSchema>>,
+    upcasters: DashMap<(String, u32), Upcaster>,
+}
+
+impl SchemaRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a schema version for an event type
+    pub fn register(&self, schema: EventSchema) -> Result<()> {
+        if schema.version == 0 {
+            return Err(Error::invalid_input(
+                "version",
+                "Schema versions start at 1",
+            ));
+        }
+
+        let mut versions = self.schemas.entry(schema.event_type.clone()).or_default();
+        if let Some(existing) = versions.get(&schema.version) {
+            if existing.owner != schema.owner {
+                let entity_type = format!("event schema {}", schema.event_type);
+                return Err(Error::Duplicate {
+                    entity_type,
+                    field: "version".to_string(),
+                });
+            }
+        }
+        versions.insert(schema.version, schema);
+        Ok(())
+    }
+
+    /// Register an upcaster migrating payloads from `from_version` to `from_version + 1`
+    pub fn register_upcaster<F>(
+        &self,
+        event_type: impl Into<String>,
+        from_version: u32,
+        upcaster: F,
+    ) where
+        F: Fn(Value) -> Result<Value> + Send + Sync + 'static,
+    {
+        self.upcasters
+            .insert((event_type.into(), from_version), Arc::new(upcaster));
+    }
+
+    /// Get a specific schema version
+    pub fn schema(&self, event_type: &str, version: u32) -> Option<EventSchema> {
+        self.schemas
+            .get(event_type)
+            .and_then(|versions| versions.get(&version).cloned())
+    }
+
+    /// Get the latest registered version for an event type
+    pub fn latest_version(&self, event_type: &str) -> Option<u32> {
+        self.schemas
+            .get(event_type)
+            .and_then(|versions| versions.keys().next_back().copied())
+    }
+
+    /// List all registered schemas, sorted by event type and version
+    pub fn list(&self) -> Vec<EventSchema> {
+        let mut schemas: Vec<EventSchema> = self
+            .schemas
+            .iter()
+            .flat_map(|entry| entry.value().values().cloned().collect::<Vec<_>>())
+            .collect();
+        schemas.sort_by(|a, b| (&a.event_type, a.version).cmp(&(&b.event_type, b.version)));
+        schemas
+    }
+
+    /// Resolve the version an event was published with.
+    ///
+    /// Events without an explicit version are treated as the latest
+    /// registered version.
+    pub fn event_version(&self, event: &DomainEvent) -> Option<u32> {
+        event
+            .schema_version
+            .or_else(|| self.latest_version(&event.event_type))
+    }
+
+    /// Validate an event payload against its declared schema.
+    ///
+    /// Events without a registered schema pass validation.
+    pub fn validate(&self, event: &DomainEvent) -> Result<()> {
+        let Some(version) = self.event_version(event) else {
+            return Ok(());
+        };
+
+        match self.schema(&event.event_type, version) {
+            Some(schema) => schema.validate(&event.payload).map_err(Error::Validation),
+            None => Err(Error::invalid_input(
+                "schema_version",
+                format!("No schema registered for {} v{}", event.event_type, version),
+            )),
+        }
+    }
+
+    /// Upcast an event payload to the requested version.
+    ///
+    /// Returns the event unchanged if it is already at or above the target
+    /// version. Downcasting is not supported.
+    pub fn upcast(&self, event: &DomainEvent, target_version: u32) -> Result<DomainEvent> {
+        let Some(mut version) = self.event_version(event) else {
+            return Ok(event.clone());
+        };
+        if version >= target_version {
+            return Ok(event.clone());
+        }
+
+        let mut payload = event.payload.clone();
+        while version < target_version {
+            let upcaster = self
+                .upcasters
+                .get(&(event.event_type.clone(), version))
+                .map(|u| u.value().clone())
+                .ok_or_else(|| {
+                    Error::invalid_input(
+                        "schema_version",
+                        format!(
+                            "No upcaster registered for {} from v{}",
+                            event.event_type, version
+                        ),
+                    )
+                })?;
+            payload = upcaster(payload)?;
+            version += 1;
+        }
+
+        let mut upcasted = event.clone();
+        upcasted.payload = payload;
+        upcasted.schema_version = Some(target_version);
+        Ok(upcasted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn post_schema_v1() -> EventSchema {
+        EventSchema::new(
+            "post.published",
+            1,
+            json!({
+                "type": "object",
+                "required": ["post_id", "title"],
+                "properties": {
+                    "post_id": {"type": "string"},
+                    "title": {"type": "string"}
+                }
+            }),
+        )
+    }
+
+    fn post_schema_v2() -> EventSchema {
+        EventSchema::new(
+            "post.published",
+            2,
+            json!({
+                "type": "object",
+                "required": ["post_id", "title", "author"],
+                "properties": {
+                    "post_id": {"type": "string"},
+                    "title": {"type": "string"},
+                    "author": {
+                        "type": "object",
+                        "required": ["id"],
+                        "properties": {"id": {"type": "string"}}
+                    }
+                }
+            }),
+        )
+    }
+
+    #[test]
+    fn test_validate_payload() {
+        let registry = SchemaRegistry::new();
+        registry.register(post_schema_v1()).unwrap();
+
+        let valid = DomainEvent::new("post.published", json!({"post_id": "1", "title": "Hi"}));
+        assert!(registry.validate(&valid).is_ok());
+
+        let invalid = DomainEvent::new("post.published", json!({"post_id": 1}));
+        let err = registry.validate(&invalid).unwrap_err();
+        match err {
+            Error::Validation(errors) => {
+                assert_eq!(errors.errors.len(), 2);
+                assert!(errors.errors.iter().any(|e| e.field == "payload.title"));
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+
+        let unknown = DomainEvent::new("other.event", json!(42));
+        assert!(registry.validate(&unknown).is_ok());
+    }
+
+    #[test]
+    fn test_upcast_through_versions() {
+        let registry = SchemaRegistry::new();
+        registry.register(post_schema_v1()).unwrap();
+        registry.register(post_schema_v2()).unwrap();
+        registry.register_upcaster("post.published", 1, |mut payload| {
+            payload["author"] = json!({"id": "unknown"});
+            Ok(payload)
+        });
+
+        assert_eq!(registry.latest_version("post.published"), Some(2));
+
+        let old = DomainEvent::new("post.published", json!({"post_id": "1", "title": "Hi"}))
+            .with_schema_version(1);
+        let upcasted = registry.upcast(&old, 2).unwrap();
+
+        assert_eq!(upcasted.schema_version, Some(2));
+        assert_eq!(upcasted.payload["author"]["id"], "unknown");
+        assert!(registry.validate(&upcasted).is_ok());
+        assert!(matches!(
+            registry.upcast(&old, 3),
+            Err(Error::InvalidInput { ref field, .. }) if field == "schema_version"
+        ));
+    }
+
+    #[test]
+    fn test_duplicate_version_from_other_owner() {
+        let registry = SchemaRegistry::new();
+        registry
+            .register(post_schema_v1().with_owner("core"))
+            .unwrap();
+        assert!(registry
+            .register(post_schema_v1().with_owner("some-plugin"))
+            .is_err());
+        assert_eq!(registry.list().len(), 1);
+    }
+}
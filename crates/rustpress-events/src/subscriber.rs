@@ -44,6 +44,9 @@ pub struct SubscriberConfig {
     pub retry_delay_ms: u64,
     /// Priority (higher = earlier execution)
     pub priority: i32,
+    /// Payload schema version this subscriber understands; older events
+    /// are upcast before delivery when the bus has a schema registry
+    pub schema_version: Option<u32>,
 }
 
 impl Default for SubscriberConfig {
@@ -54,6 +57,7 @@ impl Default for SubscriberConfig {
             max_retries: 3,
             retry_delay_ms: 1000,
             priority: 0,
+            schema_version: None,
         }
     }
 }
@@ -81,6 +85,11 @@ impl SubscriberConfig {
         self.priority = priority;
         self
     }
+
+    pub fn with_schema_version(mut self, version: u32) -> Self {
+        self.schema_version = Some(version);
+        self
+    }
 }
 
 /// Event subscriber
@@ -173,6 +182,7 @@ pub struct SubscriberBuilder {
     max_retries: u32,
     retry_delay_ms: u64,
     priority: i32,
+    schema_version: Option<u32>,
 }
 
 impl SubscriberBuilder {
@@ -184,6 +194,7 @@ impl SubscriberBuilder {
             max_retries: 3,
             retry_delay_ms: 1000,
             priority: 0,
+            schema_version: None,
         }
     }
 
@@ -218,6 +229,11 @@ impl SubscriberBuilder {
         self
     }
 
+    pub fn schema_version(mut self, version: u32) -> Self {
+        self.schema_version = Some(version);
+        self
+    }
+
     pub fn build<F, Fut>(self, handler: F) -> Subscriber
     where
         F: Fn(Arc<DomainEvent>) -> Fut + Send + Sync + 'static,
@@ -233,6 +249,7 @@ impl SubscriberBuilder {
             max_retries: self.max_retries,
             retry_delay_ms: self.retry_delay_ms,
             priority: self.priority,
+            schema_version: self.schema_version,
         };
 
         Subscriber::new(name, config, handler)
@@ -0,0 +1,295 @@
+//! Audit trail of job executions.
+//!
+//! The worker records every attempt it finishes in the `job_executions`
+//! table: how long the job waited once it was due, how long the handler
+//! ran and how the attempt ended. [`ExecutionAudit::stats`] aggregates that
+//! history per job type for SLA checks and the jobs health summary.
+
+use crate::job::Job;
+use chrono::{DateTime, Utc};
+use rustpress_core::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// How an attempt ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExecutionOutcome {
+    /// The handler succeeded
+    Completed,
+    /// The attempt failed and the job was put back in line
+    Retried,
+    /// The attempt failed and the job went to the dead-letter queue
+    Failed,
+}
+
+impl ExecutionOutcome {
+    /// Name stored in the `job_executions.outcome` column
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Completed => "completed",
+            Self::Retried => "retried",
+            Self::Failed => "failed",
+        }
+    }
+
+    pub fn is_failure(&self) -> bool {
+        !matches!(self, Self::Completed)
+    }
+}
+
+/// One finished attempt of a job
+#[derive(Debug, Clone, Serialize)]
+pub struct JobExecution {
+    pub job_id: Uuid,
+    pub tenant_id: Option<Uuid>,
+    pub queue: String,
+    pub job_type: String,
+    pub attempt: u32,
+    pub outcome: ExecutionOutcome,
+    pub error: Option<String>,
+    /// When the job became due
+    pub available_at: DateTime<Utc>,
+    /// When a worker reserved it
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+}
+
+impl JobExecution {
+    /// Attempt of `job` that a worker reserved at `started_at` and that
+    /// just ended with `outcome`
+    pub fn finished(
+        job: &Job,
+        started_at: DateTime<Utc>,
+        outcome: ExecutionOutcome,
+        error: Option<&str>,
+    ) -> Self {
+        Self {
+            job_id: job.id,
+            tenant_id: job.tenant_id,
+            queue: job.queue.clone(),
+            job_type: job.job_type.clone(),
+            attempt: job.attempts,
+            outcome,
+            error: error.map(str::to_string),
+            available_at: job.available_at,
+            started_at: job.reserved_at.unwrap_or(started_at),
+            finished_at: Utc::now(),
+        }
+    }
+
+    /// Time the job waited between becoming due and being reserved
+    pub fn queue_latency_ms(&self) -> i64 {
+        (self.started_at - self.available_at)
+            .num_milliseconds()
+            .max(0)
+    }
+
+    /// Time the attempt took
+    pub fn duration_ms(&self) -> i64 {
+        (self.finished_at - self.started_at)
+            .num_milliseconds()
+            .max(0)
+    }
+}
+
+/// Execution history of one job type over a window
+#[derive(Debug, Clone, Default, Serialize, Deserialize, sqlx::FromRow)]
+pub struct JobTypeStats {
+    pub job_type: String,
+    pub executions: i64,
+    pub completed: i64,
+    pub retried: i64,
+    pub failed: i64,
+    pub avg_duration_ms: f64,
+    pub p95_duration_ms: f64,
+    pub max_duration_ms: i64,
+    pub avg_queue_latency_ms: f64,
+    pub p95_queue_latency_ms: f64,
+    pub max_queue_latency_ms: i64,
+    pub last_finished_at: Option<DateTime<Utc>>,
+}
+
+impl JobTypeStats {
+    /// Stats of a job type that did not run in the window
+    pub fn empty(job_type: impl Into<String>) -> Self {
+        Self {
+            job_type: job_type.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Share of attempts that did not complete, `0.0` without attempts
+    pub fn failure_rate(&self) -> f64 {
+        if self.executions == 0 {
+            return 0.0;
+        }
+        (self.retried + self.failed) as f64 / self.executions as f64
+    }
+}
+
+/// Job attempts kept in the `job_executions` table
+pub struct ExecutionAudit {
+    pool: PgPool,
+}
+
+impl ExecutionAudit {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Store a finished attempt
+    pub async fn record(&self, execution: &JobExecution) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO job_executions (
+                job_id, tenant_id, queue, job_type, attempt, outcome, error,
+                available_at, started_at, finished_at, queue_latency_ms, duration_ms
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+            "#,
+        )
+        .bind(execution.job_id)
+        .bind(execution.tenant_id)
+        .bind(&execution.queue)
+        .bind(&execution.job_type)
+        .bind(execution.attempt as i32)
+        .bind(execution.outcome.as_str())
+        .bind(execution.error.as_deref())
+        .bind(execution.available_at)
+        .bind(execution.started_at)
+        .bind(execution.finished_at)
+        .bind(execution.queue_latency_ms())
+        .bind(execution.duration_ms())
+        .execute(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to record job execution", e))?;
+
+        Ok(())
+    }
+
+    /// Per-job-type stats of attempts finished since `since`
+    pub async fn stats(&self, since: DateTime<Utc>) -> Result<Vec<JobTypeStats>> {
+        sqlx::query_as(
+            r#"
+            SELECT
+                job_type,
+                COUNT(*) AS executions,
+                COUNT(*) FILTER (WHERE outcome = 'completed') AS completed,
+                COUNT(*) FILTER (WHERE outcome = 'retried') AS retried,
+                COUNT(*) FILTER (WHERE outcome = 'failed') AS failed,
+                AVG(duration_ms)::float8 AS avg_duration_ms,
+                percentile_cont(0.95) WITHIN GROUP (ORDER BY duration_ms) AS p95_duration_ms,
+                MAX(duration_ms) AS max_duration_ms,
+                AVG(queue_latency_ms)::float8 AS avg_queue_latency_ms,
+                percentile_cont(0.95) WITHIN GROUP (ORDER BY queue_latency_ms)
+                    AS p95_queue_latency_ms,
+                MAX(queue_latency_ms) AS max_queue_latency_ms,
+                MAX(finished_at) AS last_finished_at
+            FROM job_executions
+            WHERE finished_at >= $1
+            GROUP BY job_type
+            ORDER BY job_type
+            "#,
+        )
+        .bind(since)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load job execution stats", e))
+    }
+
+    /// Most recent attempts, of `job_type` only when given
+    pub async fn recent(&self, job_type: Option<&str>, limit: i64) -> Result<Vec<ExecutionRecord>> {
+        sqlx::query_as(
+            r#"
+            SELECT id, job_id, queue, job_type, attempt, outcome, error,
+                   available_at, started_at, finished_at, queue_latency_ms, duration_ms
+            FROM job_executions
+            WHERE ($1::text IS NULL OR job_type = $1)
+            ORDER BY finished_at DESC, id DESC
+            LIMIT $2
+            "#,
+        )
+        .bind(job_type)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to list job executions", e))
+    }
+
+    /// Drop attempts finished before `before`; returns how many went
+    pub async fn prune(&self, before: DateTime<Utc>) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM job_executions WHERE finished_at < $1")
+            .bind(before)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to prune job executions", e))?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+/// A stored attempt
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ExecutionRecord {
+    pub id: i64,
+    pub job_id: Uuid,
+    pub queue: String,
+    pub job_type: String,
+    pub attempt: i32,
+    pub outcome: String,
+    pub error: Option<String>,
+    pub available_at: DateTime<Utc>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub queue_latency_ms: i64,
+    pub duration_ms: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::job::jobs::SendEmailJob;
+
+    #[test]
+    fn test_execution_timings() {
+        let mut job = Job::new(SendEmailJob {
+            to: "reader@example.com".to_string(),
+            subject: "Welcome".to_string(),
+            body: "Hello".to_string(),
+            html: false,
+        });
+        let due = Utc::now() - chrono::Duration::seconds(30);
+        job.available_at = due;
+        job.reserved_at = Some(due + chrono::Duration::seconds(12));
+        job.attempts = 2;
+
+        let execution =
+            JobExecution::finished(&job, Utc::now(), ExecutionOutcome::Retried, Some("timeout"));
+        assert_eq!(execution.attempt, 2);
+        assert_eq!(execution.queue_latency_ms(), 12_000);
+        assert!(execution.duration_ms() >= 18_000);
+        assert!(execution.outcome.is_failure());
+
+        // A job reserved before it became due never reports negative waits
+        job.reserved_at = Some(due - chrono::Duration::seconds(5));
+        let execution = JobExecution::finished(&job, Utc::now(), ExecutionOutcome::Completed, None);
+        assert_eq!(execution.queue_latency_ms(), 0);
+    }
+
+    #[test]
+    fn test_failure_rate() {
+        assert_eq!(JobTypeStats::empty("send_email").failure_rate(), 0.0);
+
+        let stats = JobTypeStats {
+            job_type: "send_email".to_string(),
+            executions: 8,
+            completed: 6,
+            retried: 1,
+            failed: 1,
+            ..Default::default()
+        };
+        assert_eq!(stats.failure_rate(), 0.25);
+    }
+}
@@ -0,0 +1,987 @@
+//! Queue storage backends.
+//!
+//! A [`JobQueue`](crate::JobQueue) keeps its jobs in a [`QueueBackend`], so
+//! several server replicas can share one queue. A reserved job is leased
+//! for the queue's visibility timeout; the worker running it renews the
+//! lease with heartbeats, and a job whose lease runs out (because its
+//! worker died) becomes available to the other replicas again, unless it
+//! has used its last attempt.
+//!
+//! Completing, failing, releasing or burying a job only takes effect while
+//! the caller still holds the lease it reserved the job with; a worker whose
+//! lease ran out cannot overwrite the outcome of the worker that took over.
+//!
+//! [`PostgresBackend`] reserves rows with `FOR UPDATE SKIP LOCKED` and is
+//! the default. [`RedisBackend`] (feature `redis`) keeps jobs in Redis
+//! streams read through a consumer group.
+
+use crate::dead_letter::DeadLetter;
+#[cfg(feature = "redis")]
+use crate::dead_letter::DeadLetterQueue;
+use crate::job::{Job, JobStatus};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rustpress_core::error::{Error, Result};
+use sqlx::PgPool;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Storage behind a job queue
+#[async_trait]
+pub trait QueueBackend: Send + Sync {
+    /// Backend name, for logs and status pages
+    fn name(&self) -> &'static str;
+
+    /// Store a new job
+    async fn push(&self, job: &Job) -> Result<()>;
+
+    /// Lease the next available job of `queue` for `visibility_timeout`.
+    /// Reserved jobs whose lease ran out count as available while they have
+    /// attempts left.
+    async fn reserve(
+        &self,
+        queue: &str,
+        tenant_id: Option<Uuid>,
+        visibility_timeout: Duration,
+    ) -> Result<Option<Job>>;
+
+    /// Renew the lease on a reserved job; `false` when the lease was lost
+    /// to another worker
+    async fn heartbeat(&self, job: &Job) -> Result<bool>;
+
+    /// Mark a reserved job as completed
+    async fn complete(&self, job: &Job) -> Result<()>;
+
+    /// Mark a reserved job as failed
+    async fn fail(&self, job: &Job, error: &str) -> Result<()>;
+
+    /// Put a reserved job back in line after `delay_secs`, recording
+    /// `error` when it failed
+    async fn release(&self, job: &Job, delay_secs: u64, error: Option<&str>) -> Result<()>;
+
+    /// Move a reserved job that ran out of attempts to the dead-letter queue
+    async fn bury(&self, job: &Job, error: &str) -> Result<()>;
+
+    /// Delete a job
+    async fn delete(&self, job_id: Uuid) -> Result<()>;
+
+    /// Get job by ID
+    async fn get(&self, job_id: Uuid) -> Result<Option<Job>>;
+
+    /// Number of jobs waiting in `queue`
+    async fn size(&self, queue: &str) -> Result<u64>;
+
+    /// Delete every job of `queue`
+    async fn clear(&self, queue: &str) -> Result<u64>;
+
+    /// Put failed jobs of `queue` with attempts left back in line
+    async fn retry_failed(&self, queue: &str) -> Result<u64>;
+
+    /// Release reserved jobs whose lease is older than `older_than_secs`;
+    /// those that used their last attempt are marked failed instead
+    async fn release_stale(&self, older_than_secs: u64) -> Result<u64>;
+}
+
+// =============================================================================
+// Postgres
+// =============================================================================
+
+/// Jobs kept in the `jobs` table and reserved with `SKIP LOCKED`
+pub struct PostgresBackend {
+    pool: PgPool,
+}
+
+impl PostgresBackend {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl QueueBackend for PostgresBackend {
+    fn name(&self) -> &'static str {
+        "postgres"
+    }
+
+    async fn push(&self, job: &Job) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO jobs (id, tenant_id, queue, job_type, payload, status, priority, attempts, max_attempts, available_at, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            "#,
+        )
+        .bind(job.id)
+        .bind(job.tenant_id)
+        .bind(&job.queue)
+        .bind(&job.job_type)
+        .bind(&job.payload)
+        .bind(job.status.as_str())
+        .bind(job.priority)
+        .bind(job.attempts as i32)
+        .bind(job.max_attempts as i32)
+        .bind(job.available_at)
+        .bind(job.created_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to push job", e))?;
+
+        Ok(())
+    }
+
+    async fn reserve(
+        &self,
+        queue: &str,
+        tenant_id: Option<Uuid>,
+        visibility_timeout: Duration,
+    ) -> Result<Option<Job>> {
+        let job: Option<JobRow> = sqlx::query_as(
+            r#"
+            UPDATE jobs
+            SET status = 'reserved', reserved_at = NOW(), attempts = attempts + 1
+            WHERE id = (
+                SELECT id FROM jobs
+                WHERE queue = $1
+                AND ($2::uuid IS NULL OR tenant_id = $2)
+                AND (
+                    (status = 'pending' AND available_at <= NOW())
+                    OR (
+                        status = 'reserved'
+                        AND reserved_at < NOW() - make_interval(secs => $3)
+                        AND attempts < max_attempts
+                    )
+                )
+                ORDER BY priority DESC, available_at ASC
+                FOR UPDATE SKIP LOCKED
+                LIMIT 1
+            )
+            RETURNING id, tenant_id, queue, job_type, payload, status, priority, attempts, max_attempts, last_error, available_at, reserved_at, completed_at, created_at
+            "#,
+        )
+        .bind(queue)
+        .bind(tenant_id)
+        .bind(visibility_timeout.as_secs_f64())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to pop job", e))?;
+
+        Ok(job.map(|r| r.into()))
+    }
+
+    async fn heartbeat(&self, job: &Job) -> Result<bool> {
+        // Every reservation bumps the attempt count, so a matching count
+        // means nobody has reserved the job since
+        let result = sqlx::query(
+            "UPDATE jobs SET reserved_at = NOW() WHERE id = $1 AND status = 'reserved' AND attempts = $2",
+        )
+        .bind(job.id)
+        .bind(job.attempts as i32)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to renew job lease", e))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn complete(&self, job: &Job) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE jobs SET status = 'completed', completed_at = NOW()
+            WHERE id = $1 AND status = 'reserved' AND attempts = $2
+            "#,
+        )
+        .bind(job.id)
+        .bind(job.attempts as i32)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to complete job", e))?;
+
+        Ok(())
+    }
+
+    async fn fail(&self, job: &Job, error: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE jobs SET status = 'failed', last_error = $3, reserved_at = NULL
+            WHERE id = $1 AND status = 'reserved' AND attempts = $2
+            "#,
+        )
+        .bind(job.id)
+        .bind(job.attempts as i32)
+        .bind(error)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to fail job", e))?;
+
+        Ok(())
+    }
+
+    async fn release(&self, job: &Job, delay_secs: u64, error: Option<&str>) -> Result<()> {
+        let available_at = Utc::now() + chrono::Duration::seconds(delay_secs as i64);
+
+        sqlx::query(
+            r#"
+            UPDATE jobs
+            SET status = 'pending', reserved_at = NULL, available_at = $3,
+                last_error = COALESCE($4, last_error)
+            WHERE id = $1 AND status = 'reserved' AND attempts = $2
+            "#,
+        )
+        .bind(job.id)
+        .bind(job.attempts as i32)
+        .bind(available_at)
+        .bind(error)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to release job", e))?;
+
+        Ok(())
+    }
+
+    async fn bury(&self, job: &Job, error: &str) -> Result<()> {
+        let letter = DeadLetter::from_job(job, error);
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| Error::database_with_source("Failed to start transaction", e))?;
+
+        let deleted =
+            sqlx::query("DELETE FROM jobs WHERE id = $1 AND status = 'reserved' AND attempts = $2")
+                .bind(job.id)
+                .bind(job.attempts as i32)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| Error::database_with_source("Failed to bury job", e))?;
+        // The lease was lost; the job belongs to another worker now
+        if deleted.rows_affected() == 0 {
+            return Ok(());
+        }
+        insert_dead_letter(&mut *tx, &letter).await?;
+
+        tx.commit()
+            .await
+            .map_err(|e| Error::database_with_source("Failed to bury job", e))?;
+
+        Ok(())
+    }
+
+    async fn delete(&self, job_id: Uuid) -> Result<()> {
+        sqlx::query("DELETE FROM jobs WHERE id = $1")
+            .bind(job_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to delete job", e))?;
+
+        Ok(())
+    }
+
+    async fn get(&self, job_id: Uuid) -> Result<Option<Job>> {
+        let job: Option<JobRow> = sqlx::query_as("SELECT * FROM jobs WHERE id = $1")
+            .bind(job_id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to get job", e))?;
+
+        Ok(job.map(|r| r.into()))
+    }
+
+    async fn size(&self, queue: &str) -> Result<u64> {
+        let (count,): (i64,) =
+            sqlx::query_as("SELECT COUNT(*) FROM jobs WHERE queue = $1 AND status = 'pending'")
+                .bind(queue)
+                .fetch_one(&self.pool)
+                .await
+                .map_err(|e| Error::database_with_source("Failed to get queue size", e))?;
+
+        Ok(count as u64)
+    }
+
+    async fn clear(&self, queue: &str) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM jobs WHERE queue = $1")
+            .bind(queue)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to clear queue", e))?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn retry_failed(&self, queue: &str) -> Result<u64> {
+        let result = sqlx::query(
+            r#"
+            UPDATE jobs
+            SET status = 'pending', reserved_at = NULL, available_at = NOW()
+            WHERE queue = $1 AND status = 'failed' AND attempts < max_attempts
+            "#,
+        )
+        .bind(queue)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to retry failed jobs", e))?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn release_stale(&self, older_than_secs: u64) -> Result<u64> {
+        let threshold = Utc::now() - chrono::Duration::seconds(older_than_secs as i64);
+
+        let result = sqlx::query(
+            r#"
+            UPDATE jobs
+            SET status = CASE WHEN attempts < max_attempts THEN 'pending' ELSE 'failed' END,
+                last_error = CASE
+                    WHEN attempts < max_attempts THEN last_error
+                    ELSE 'Job lease expired on its last attempt'
+                END,
+                reserved_at = NULL
+            WHERE status = 'reserved' AND reserved_at < $1
+            "#,
+        )
+        .bind(threshold)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to release stale jobs", e))?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+/// Insert or refresh a dead letter
+pub(crate) async fn insert_dead_letter<'e, E>(executor: E, letter: &DeadLetter) -> Result<()>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+{
+    sqlx::query(
+        r#"
+        INSERT INTO job_dead_letters (id, tenant_id, queue, job_type, payload, priority, attempts, max_attempts, error, job_created_at, failed_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+        ON CONFLICT (id) DO UPDATE SET
+            attempts = EXCLUDED.attempts,
+            error = EXCLUDED.error,
+            failed_at = EXCLUDED.failed_at
+        "#,
+    )
+    .bind(letter.id)
+    .bind(letter.tenant_id)
+    .bind(&letter.queue)
+    .bind(&letter.job_type)
+    .bind(&letter.payload)
+    .bind(letter.priority)
+    .bind(letter.attempts)
+    .bind(letter.max_attempts)
+    .bind(&letter.error)
+    .bind(letter.job_created_at)
+    .bind(letter.failed_at)
+    .execute(executor)
+    .await
+    .map_err(|e| Error::database_with_source("Failed to bury job", e))?;
+
+    Ok(())
+}
+
+/// Database row for jobs
+#[derive(sqlx::FromRow)]
+struct JobRow {
+    id: Uuid,
+    tenant_id: Option<Uuid>,
+    queue: String,
+    job_type: String,
+    payload: serde_json::Value,
+    status: String,
+    priority: Option<i32>,
+    attempts: Option<i32>,
+    max_attempts: Option<i32>,
+    last_error: Option<String>,
+    available_at: DateTime<Utc>,
+    reserved_at: Option<DateTime<Utc>>,
+    completed_at: Option<DateTime<Utc>>,
+    created_at: DateTime<Utc>,
+}
+
+impl From<JobRow> for Job {
+    fn from(row: JobRow) -> Self {
+        Job {
+            id: row.id,
+            tenant_id: row.tenant_id,
+            queue: row.queue,
+            job_type: row.job_type,
+            payload: row.payload,
+            status: JobStatus::from_name(&row.status).unwrap_or(JobStatus::Pending),
+            priority: row.priority.unwrap_or(0),
+            attempts: row.attempts.unwrap_or(0) as u32,
+            max_attempts: row.max_attempts.unwrap_or(3) as u32,
+            timeout_secs: 300,
+            last_error: row.last_error,
+            available_at: row.available_at,
+            reserved_at: row.reserved_at,
+            completed_at: row.completed_at,
+            created_at: row.created_at,
+        }
+    }
+}
+
+// =============================================================================
+// Redis
+// =============================================================================
+
+/// Consumer group every replica reads queue streams through
+#[cfg(feature = "redis")]
+const CONSUMER_GROUP: &str = "workers";
+
+/// Move due delayed jobs onto the stream `KEYS[1]`, then lease one entry to
+/// consumer `ARGV[2]`: an entry whose lease outlived `ARGV[4]` ms if there
+/// is one, else a new entry. Returns `{entry id, job id}` or nil.
+#[cfg(feature = "redis")]
+const RESERVE_SCRIPT: &str = r#"
+local due = redis.call('ZRANGEBYSCORE', KEYS[2], '-inf', ARGV[3], 'LIMIT', 0, 100)
+for _, id in ipairs(due) do
+    redis.call('ZREM', KEYS[2], id)
+    redis.call('XADD', KEYS[1], '*', 'id', id)
+end
+redis.pcall('XGROUP', 'CREATE', KEYS[1], ARGV[1], '0', 'MKSTREAM')
+local entry
+local claimed = redis.call('XAUTOCLAIM', KEYS[1], ARGV[1], ARGV[2], ARGV[4], '0-0', 'COUNT', 1)
+if claimed[2][1] and claimed[2][1][2] then
+    entry = claimed[2][1]
+else
+    local read = redis.call('XREADGROUP', 'GROUP', ARGV[1], ARGV[2], 'COUNT', 1, 'STREAMS', KEYS[1], '>')
+    if read and read[1] and read[1][2][1] then
+        entry = read[1][2][1]
+    end
+end
+if not entry then
+    return nil
+end
+redis.call('HSET', KEYS[3], entry[2][2], entry[1])
+return {entry[1], entry[2][2]}
+"#;
+
+/// Renew the lease on entry `ARGV[3]` of stream `KEYS[1]` if consumer
+/// `ARGV[2]` still holds it. Returns 1 when renewed.
+#[cfg(feature = "redis")]
+const HEARTBEAT_SCRIPT: &str = r#"
+local pending = redis.call('XPENDING', KEYS[1], ARGV[1], ARGV[3], ARGV[3], 1)
+if pending[1] == nil or pending[1][2] ~= ARGV[2] then
+    return 0
+end
+redis.call('XCLAIM', KEYS[1], ARGV[1], ARGV[2], 0, ARGV[3], 'JUSTID')
+return 1
+"#;
+
+/// Jobs kept in Redis: one stream per queue read through a consumer group,
+/// with delayed jobs in a sorted set until they are due.
+///
+/// Streams are first in, first out, so job priorities are ignored.
+/// Completed jobs are removed rather than kept, and dead letters still go
+/// to the `job_dead_letters` table so the admin API sees them.
+#[cfg(feature = "redis")]
+pub struct RedisBackend {
+    pool: deadpool_redis::Pool,
+    prefix: String,
+    /// This replica's name within the consumer group
+    consumer: String,
+    dead_letters: DeadLetterQueue,
+}
+
+#[cfg(feature = "redis")]
+impl RedisBackend {
+    pub fn new(url: &str, dead_letters: DeadLetterQueue) -> Result<Self> {
+        let cfg = deadpool_redis::Config::from_url(url);
+        let pool = cfg
+            .create_pool(Some(deadpool_redis::Runtime::Tokio1))
+            .map_err(|e| Error::Configuration {
+                message: format!("Failed to create Redis pool: {}", e),
+            })?;
+
+        Ok(Self {
+            pool,
+            prefix: "rustpress:jobs".to_string(),
+            consumer: format!("worker-{}", Uuid::now_v7()),
+            dead_letters,
+        })
+    }
+
+    /// Prefix of every key the backend uses
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    async fn connection(&self) -> Result<deadpool_redis::Connection> {
+        self.pool
+            .get()
+            .await
+            .map_err(|e| redis_error("connection", e))
+    }
+
+    fn job_key(&self, job_id: Uuid) -> String {
+        format!("{}:job:{}", self.prefix, job_id)
+    }
+
+    fn leases_key(&self) -> String {
+        format!("{}:leases", self.prefix)
+    }
+
+    /// Stream of `queue`; tenant-scoped jobs get a stream of their own
+    fn stream_key(&self, queue: &str, tenant_id: Option<Uuid>) -> String {
+        match tenant_id {
+            Some(tenant_id) => format!("{}:queue:{}:{}", self.prefix, tenant_id, queue),
+            None => format!("{}:queue:{}", self.prefix, queue),
+        }
+    }
+
+    fn job_stream_key(&self, job: &Job) -> String {
+        self.stream_key(&job.queue, job.tenant_id)
+    }
+
+    async fn load(
+        &self,
+        conn: &mut deadpool_redis::Connection,
+        job_id: Uuid,
+    ) -> Result<Option<Job>> {
+        let raw: Option<String> = redis::cmd("GET")
+            .arg(self.job_key(job_id))
+            .query_async(&mut **conn)
+            .await
+            .map_err(|e| redis_error("GET", e))?;
+
+        raw.map(|raw| {
+            serde_json::from_str(&raw).map_err(|e| Error::Job {
+                job_id: job_id.to_string(),
+                message: format!("Failed to deserialize job: {}", e),
+            })
+        })
+        .transpose()
+    }
+
+    async fn store(&self, conn: &mut deadpool_redis::Connection, job: &Job) -> Result<()> {
+        let raw = serde_json::to_string(job).map_err(|e| Error::Serialization {
+            message: e.to_string(),
+        })?;
+        redis::cmd("SET")
+            .arg(self.job_key(job.id))
+            .arg(raw)
+            .query_async::<_, ()>(&mut **conn)
+            .await
+            .map_err(|e| redis_error("SET", e))
+    }
+
+    /// Put a stored job in line, on the stream when due and in the delayed
+    /// set otherwise
+    async fn enqueue(&self, conn: &mut deadpool_redis::Connection, job: &Job) -> Result<()> {
+        let stream = self.job_stream_key(job);
+        let mut pipe = redis::pipe();
+        pipe.atomic()
+            .cmd("SADD")
+            .arg(format!("{}:jobs", stream))
+            .arg(job.id.to_string())
+            .ignore();
+        if job.available_at <= Utc::now() {
+            pipe.cmd("XADD")
+                .arg(&stream)
+                .arg("*")
+                .arg("id")
+                .arg(job.id.to_string())
+                .ignore();
+        } else {
+            pipe.cmd("ZADD")
+                .arg(format!("{}:delayed", stream))
+                .arg(job.available_at.timestamp_millis())
+                .arg(job.id.to_string())
+                .ignore();
+        }
+        pipe.query_async::<_, ()>(&mut **conn)
+            .await
+            .map_err(|e| redis_error("enqueue", e))
+    }
+
+    /// Drop the stream entry leasing `job`, if any
+    async fn ack(&self, conn: &mut deadpool_redis::Connection, job: &Job) -> Result<()> {
+        let entry: Option<String> = redis::cmd("HGET")
+            .arg(self.leases_key())
+            .arg(job.id.to_string())
+            .query_async(&mut **conn)
+            .await
+            .map_err(|e| redis_error("HGET", e))?;
+        let Some(entry) = entry else {
+            return Ok(());
+        };
+
+        let stream = self.job_stream_key(job);
+        redis::pipe()
+            .atomic()
+            .cmd("XACK")
+            .arg(&stream)
+            .arg(CONSUMER_GROUP)
+            .arg(&entry)
+            .ignore()
+            .cmd("XDEL")
+            .arg(&stream)
+            .arg(&entry)
+            .ignore()
+            .cmd("HDEL")
+            .arg(self.leases_key())
+            .arg(job.id.to_string())
+            .ignore()
+            .query_async::<_, ()>(&mut **conn)
+            .await
+            .map_err(|e| redis_error("XACK", e))
+    }
+
+    /// Remove every trace of `job` from Redis
+    async fn forget(&self, conn: &mut deadpool_redis::Connection, job: &Job) -> Result<()> {
+        self.ack(conn, job).await?;
+        let stream = self.job_stream_key(job);
+        redis::pipe()
+            .atomic()
+            .cmd("DEL")
+            .arg(self.job_key(job.id))
+            .ignore()
+            .cmd("SREM")
+            .arg(format!("{}:jobs", stream))
+            .arg(job.id.to_string())
+            .ignore()
+            .cmd("SREM")
+            .arg(format!("{}:failed", stream))
+            .arg(job.id.to_string())
+            .ignore()
+            .cmd("ZREM")
+            .arg(format!("{}:delayed", stream))
+            .arg(job.id.to_string())
+            .ignore()
+            .query_async::<_, ()>(&mut **conn)
+            .await
+            .map_err(|e| redis_error("DEL", e))
+    }
+
+    /// The stored job, while `job`'s lease on it has not been taken over;
+    /// every reservation bumps the attempt count
+    async fn held(&self, conn: &mut deadpool_redis::Connection, job: &Job) -> Result<Option<Job>> {
+        Ok(self.load(conn, job.id).await?.filter(|stored| {
+            stored.status == JobStatus::Reserved && stored.attempts == job.attempts
+        }))
+    }
+}
+
+#[cfg(feature = "redis")]
+fn redis_error(action: &str, e: impl std::fmt::Display) -> Error {
+    Error::ServiceUnavailable {
+        service: format!("Redis job queue ({} failed: {})", action, e),
+    }
+}
+
+#[cfg(feature = "redis")]
+#[async_trait]
+impl QueueBackend for RedisBackend {
+    fn name(&self) -> &'static str {
+        "redis"
+    }
+
+    async fn push(&self, job: &Job) -> Result<()> {
+        let mut conn = self.connection().await?;
+        self.store(&mut conn, job).await?;
+        self.enqueue(&mut conn, job).await
+    }
+
+    async fn reserve(
+        &self,
+        queue: &str,
+        tenant_id: Option<Uuid>,
+        visibility_timeout: Duration,
+    ) -> Result<Option<Job>> {
+        let mut conn = self.connection().await?;
+        let stream = self.stream_key(queue, tenant_id);
+
+        // Entries whose job was deleted meanwhile are dropped and skipped
+        loop {
+            let leased: Option<(String, String)> = redis::Script::new(RESERVE_SCRIPT)
+                .key(&stream)
+                .key(format!("{}:delayed", stream))
+                .key(self.leases_key())
+                .arg(CONSUMER_GROUP)
+                .arg(&self.consumer)
+                .arg(Utc::now().timestamp_millis())
+                .arg(visibility_timeout.as_millis() as u64)
+                .invoke_async(&mut *conn)
+                .await
+                .map_err(|e| redis_error("reserve", e))?;
+            let Some((entry, job_id)) = leased else {
+                return Ok(None);
+            };
+
+            let job = match Uuid::parse_str(&job_id) {
+                Ok(id) => self.load(&mut conn, id).await?,
+                Err(_) => None,
+            };
+            let Some(mut job) = job else {
+                redis::pipe()
+                    .cmd("XACK")
+                    .arg(&stream)
+                    .arg(CONSUMER_GROUP)
+                    .arg(&entry)
+                    .ignore()
+                    .cmd("XDEL")
+                    .arg(&stream)
+                    .arg(&entry)
+                    .ignore()
+                    .cmd("HDEL")
+                    .arg(self.leases_key())
+                    .arg(&job_id)
+                    .ignore()
+                    .query_async::<_, ()>(&mut *conn)
+                    .await
+                    .map_err(|e| redis_error("XACK", e))?;
+                continue;
+            };
+
+            job.reserve();
+            self.store(&mut conn, &job).await?;
+            return Ok(Some(job));
+        }
+    }
+
+    async fn heartbeat(&self, job: &Job) -> Result<bool> {
+        let mut conn = self.connection().await?;
+        let entry: Option<String> = redis::cmd("HGET")
+            .arg(self.leases_key())
+            .arg(job.id.to_string())
+            .query_async(&mut *conn)
+            .await
+            .map_err(|e| redis_error("HGET", e))?;
+        let Some(entry) = entry else {
+            return Ok(false);
+        };
+
+        let renewed: i64 = redis::Script::new(HEARTBEAT_SCRIPT)
+            .key(self.job_stream_key(job))
+            .arg(CONSUMER_GROUP)
+            .arg(&self.consumer)
+            .arg(entry)
+            .invoke_async(&mut *conn)
+            .await
+            .map_err(|e| redis_error("heartbeat", e))?;
+
+        Ok(renewed == 1)
+    }
+
+    async fn complete(&self, job: &Job) -> Result<()> {
+        let mut conn = self.connection().await?;
+        if let Some(job) = self.held(&mut conn, job).await? {
+            self.forget(&mut conn, &job).await?;
+        }
+        Ok(())
+    }
+
+    async fn fail(&self, job: &Job, error: &str) -> Result<()> {
+        let mut conn = self.connection().await?;
+        let Some(mut job) = self.held(&mut conn, job).await? else {
+            return Ok(());
+        };
+        self.ack(&mut conn, &job).await?;
+
+        job.fail(error);
+        self.store(&mut conn, &job).await?;
+        redis::cmd("SADD")
+            .arg(format!("{}:failed", self.job_stream_key(&job)))
+            .arg(job.id.to_string())
+            .query_async::<_, ()>(&mut *conn)
+            .await
+            .map_err(|e| redis_error("SADD", e))
+    }
+
+    async fn release(&self, job: &Job, delay_secs: u64, error: Option<&str>) -> Result<()> {
+        let mut conn = self.connection().await?;
+        let Some(mut job) = self.held(&mut conn, job).await? else {
+            return Ok(());
+        };
+        self.ack(&mut conn, &job).await?;
+
+        job.release(delay_secs);
+        if let Some(error) = error {
+            job.last_error = Some(error.to_string());
+        }
+        self.store(&mut conn, &job).await?;
+        self.enqueue(&mut conn, &job).await
+    }
+
+    async fn bury(&self, job: &Job, error: &str) -> Result<()> {
+        let mut conn = self.connection().await?;
+        if self.held(&mut conn, job).await?.is_none() {
+            return Ok(());
+        }
+        self.dead_letters
+            .add(&DeadLetter::from_job(job, error))
+            .await?;
+        self.forget(&mut conn, job).await
+    }
+
+    async fn delete(&self, job_id: Uuid) -> Result<()> {
+        let mut conn = self.connection().await?;
+        if let Some(job) = self.load(&mut conn, job_id).await? {
+            self.forget(&mut conn, &job).await?;
+        }
+        Ok(())
+    }
+
+    async fn get(&self, job_id: Uuid) -> Result<Option<Job>> {
+        let mut conn = self.connection().await?;
+        self.load(&mut conn, job_id).await
+    }
+
+    async fn size(&self, queue: &str) -> Result<u64> {
+        let mut conn = self.connection().await?;
+        let stream = self.stream_key(queue, None);
+
+        let (length, delayed): (u64, u64) = redis::pipe()
+            .cmd("XLEN")
+            .arg(&stream)
+            .cmd("ZCARD")
+            .arg(format!("{}:delayed", stream))
+            .query_async(&mut *conn)
+            .await
+            .map_err(|e| redis_error("XLEN", e))?;
+        // Leased entries stay on the stream until acknowledged
+        let pending: redis::Value = redis::cmd("XPENDING")
+            .arg(&stream)
+            .arg(CONSUMER_GROUP)
+            .query_async(&mut *conn)
+            .await
+            .unwrap_or(redis::Value::Nil);
+        let leased = match pending {
+            redis::Value::Bulk(ref summary) => match summary.first() {
+                Some(redis::Value::Int(count)) => *count as u64,
+                _ => 0,
+            },
+            _ => 0,
+        };
+
+        Ok(length.saturating_sub(leased) + delayed)
+    }
+
+    async fn clear(&self, queue: &str) -> Result<u64> {
+        let mut conn = self.connection().await?;
+        let stream = self.stream_key(queue, None);
+
+        let ids: Vec<String> = redis::cmd("SMEMBERS")
+            .arg(format!("{}:jobs", stream))
+            .query_async(&mut *conn)
+            .await
+            .map_err(|e| redis_error("SMEMBERS", e))?;
+
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        for id in &ids {
+            pipe.cmd("DEL")
+                .arg(format!("{}:job:{}", self.prefix, id))
+                .ignore()
+                .cmd("HDEL")
+                .arg(self.leases_key())
+                .arg(id)
+                .ignore();
+        }
+        for key in [
+            stream.clone(),
+            format!("{}:jobs", stream),
+            format!("{}:delayed", stream),
+            format!("{}:failed", stream),
+        ] {
+            pipe.cmd("DEL").arg(key).ignore();
+        }
+        pipe.query_async::<_, ()>(&mut *conn)
+            .await
+            .map_err(|e| redis_error("clear", e))?;
+
+        Ok(ids.len() as u64)
+    }
+
+    async fn retry_failed(&self, queue: &str) -> Result<u64> {
+        let mut conn = self.connection().await?;
+        let failed_key = format!("{}:failed", self.stream_key(queue, None));
+
+        let ids: Vec<String> = redis::cmd("SMEMBERS")
+            .arg(&failed_key)
+            .query_async(&mut *conn)
+            .await
+            .map_err(|e| redis_error("SMEMBERS", e))?;
+
+        let mut retried = 0;
+        for id in ids {
+            let Ok(job_id) = Uuid::parse_str(&id) else {
+                continue;
+            };
+            let Some(mut job) = self.load(&mut conn, job_id).await? else {
+                continue;
+            };
+            if !job.can_retry() {
+                continue;
+            }
+
+            job.release(0);
+            self.store(&mut conn, &job).await?;
+            self.enqueue(&mut conn, &job).await?;
+            redis::cmd("SREM")
+                .arg(&failed_key)
+                .arg(&id)
+                .query_async::<_, ()>(&mut *conn)
+                .await
+                .map_err(|e| redis_error("SREM", e))?;
+            retried += 1;
+        }
+
+        Ok(retried)
+    }
+
+    /// Entries whose lease ran out are reclaimed on the next reserve, so
+    /// there is nothing to sweep
+    async fn release_stale(&self, _older_than_secs: u64) -> Result<u64> {
+        Ok(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_job_row_status() {
+        let now = Utc::now();
+        let row = JobRow {
+            id: Uuid::now_v7(),
+            tenant_id: None,
+            queue: "emails".to_string(),
+            job_type: "send_email".to_string(),
+            payload: serde_json::json!({}),
+            status: "reserved".to_string(),
+            priority: None,
+            attempts: Some(2),
+            max_attempts: None,
+            last_error: None,
+            available_at: now,
+            reserved_at: Some(now),
+            completed_at: None,
+            created_at: now,
+        };
+
+        let job = Job::from(row);
+        assert_eq!(job.status, JobStatus::Reserved);
+        assert_eq!(job.attempts, 2);
+        assert_eq!(job.max_attempts, 3);
+    }
+
+    #[cfg(feature = "redis")]
+    #[tokio::test]
+    async fn test_redis_keys() {
+        let pool = sqlx::PgPool::connect_lazy("postgres://localhost/rustpress_test").unwrap();
+        let backend = RedisBackend::new("redis://localhost", DeadLetterQueue::new(pool))
+            .unwrap()
+            .with_prefix("site");
+        let tenant = Uuid::nil();
+
+        assert_eq!(backend.stream_key("emails", None), "site:queue:emails");
+        assert_eq!(
+            backend.stream_key("emails", Some(tenant)),
+            format!("site:queue:{}:emails", tenant)
+        );
+        assert_eq!(backend.leases_key(), "site:leases");
+    }
+}
@@ -0,0 +1,537 @@
+//! Cron expressions evaluated in a timezone.
+//!
+//! Expressions have the five standard fields (minute, hour, day of month,
+//! month, day of week) with lists, ranges, steps and month or day names,
+//! or are one of the `@daily`-style macros. As in Vixie cron, a day matches
+//! if either day field does when both are restricted.
+//!
+//! Daylight saving transitions follow what cron users expect: a job at a
+//! fixed local time skipped by a spring-forward transition runs right after
+//! the transition, and one at a local time that repeats when clocks go back
+//! runs once. Jobs that run every hour run in both repeated hours and not in
+//! the skipped one.
+
+use chrono::{
+    DateTime, Datelike, Duration, LocalResult, NaiveDate, NaiveDateTime, TimeZone, Timelike, Utc,
+};
+use chrono_tz::Tz;
+use rustpress_core::error::{Error, Result};
+use std::fmt;
+use std::str::FromStr;
+
+/// Years searched for the next occurrence before giving up
+const SEARCH_YEARS: i32 = 8;
+
+const MONTH_NAMES: &[&str] = &[
+    "JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC",
+];
+const DAY_NAMES: &[&str] = &["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
+
+/// Bounds and names of one cron field
+struct Field {
+    name: &'static str,
+    min: u32,
+    max: u32,
+    names: &'static [&'static str],
+}
+
+const MINUTE: Field = Field {
+    name: "minute",
+    min: 0,
+    max: 59,
+    names: &[],
+};
+const HOUR: Field = Field {
+    name: "hour",
+    min: 0,
+    max: 23,
+    names: &[],
+};
+const DAY_OF_MONTH: Field = Field {
+    name: "day of month",
+    min: 1,
+    max: 31,
+    names: &[],
+};
+const MONTH: Field = Field {
+    name: "month",
+    min: 1,
+    max: 12,
+    names: MONTH_NAMES,
+};
+// 7 is accepted as Sunday and folded into 0
+const DAY_OF_WEEK: Field = Field {
+    name: "day of week",
+    min: 0,
+    max: 7,
+    names: DAY_NAMES,
+};
+
+/// Cron schedule in a timezone
+#[derive(Debug, Clone, PartialEq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    /// Day of month was given as `*` or `?`
+    any_day_of_month: bool,
+    /// Day of week was given as `*` or `?`
+    any_day_of_week: bool,
+    timezone: Tz,
+}
+
+impl CronSchedule {
+    /// Schedule running every minute, in UTC
+    pub fn new() -> Self {
+        Self {
+            minutes: mask(0, 59),
+            hours: mask(0, 23),
+            days_of_month: mask(1, 31),
+            months: mask(1, 12),
+            days_of_week: mask(0, 6),
+            any_day_of_month: true,
+            any_day_of_week: true,
+            timezone: Tz::UTC,
+        }
+    }
+
+    /// Parse a cron expression, evaluated in UTC
+    pub fn parse(expression: &str) -> Result<Self> {
+        let expression = expression.trim();
+        let expanded = match expression.to_ascii_lowercase().as_str() {
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            "@monthly" => "0 0 1 * *",
+            "@weekly" => "0 0 * * 0",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@hourly" => "0 * * * *",
+            _ if expression.starts_with('@') => {
+                return Err(invalid(format!("unknown macro '{}'", expression)));
+            }
+            _ => expression,
+        };
+
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(invalid(format!(
+                "expected 5 fields (minute hour day month weekday), got {}",
+                fields.len()
+            )));
+        }
+
+        let days_of_week = parse_field(fields[4], &DAY_OF_WEEK)?;
+        let schedule = Self {
+            minutes: parse_field(fields[0], &MINUTE)?,
+            hours: parse_field(fields[1], &HOUR)?,
+            days_of_month: parse_field(fields[2], &DAY_OF_MONTH)?,
+            months: parse_field(fields[3], &MONTH)?,
+            days_of_week: (days_of_week | (days_of_week >> 7)) & mask(0, 6),
+            any_day_of_month: is_wildcard(fields[2]),
+            any_day_of_week: is_wildcard(fields[4]),
+            timezone: Tz::UTC,
+        };
+
+        // Only the day of month limits the days when the weekday is a
+        // wildcard, so e.g. `0 0 30 2 *` can never run
+        if !schedule.any_day_of_month && schedule.any_day_of_week {
+            let possible = (1..=12u32).any(|month| {
+                has(schedule.months, month)
+                    && (1..=days_in_month(2000, month)).any(|d| has(schedule.days_of_month, d))
+            });
+            if !possible {
+                return Err(invalid("the day of month never occurs in the given months"));
+            }
+        }
+
+        Ok(schedule)
+    }
+
+    /// Parse a cron expression evaluated in an IANA timezone, e.g.
+    /// `Europe/Berlin`
+    pub fn parse_in(expression: &str, timezone: &str) -> Result<Self> {
+        Ok(Self::parse(expression)?.in_timezone(parse_timezone(timezone)?))
+    }
+
+    /// Evaluate the schedule in `timezone`
+    pub fn in_timezone(mut self, timezone: Tz) -> Self {
+        self.timezone = timezone;
+        self
+    }
+
+    /// Timezone the schedule is evaluated in
+    pub fn timezone(&self) -> Tz {
+        self.timezone
+    }
+
+    pub fn minute(mut self, min: u32) -> Self {
+        self.minutes = 1 << min.min(MINUTE.max);
+        self
+    }
+
+    pub fn hour(mut self, hour: u32) -> Self {
+        self.hours = 1 << hour.min(HOUR.max);
+        self
+    }
+
+    pub fn day_of_month(mut self, day: u32) -> Self {
+        self.days_of_month = 1 << day.clamp(DAY_OF_MONTH.min, DAY_OF_MONTH.max);
+        self.any_day_of_month = false;
+        self
+    }
+
+    pub fn month(mut self, month: u32) -> Self {
+        self.months = 1 << month.clamp(MONTH.min, MONTH.max);
+        self
+    }
+
+    /// Restrict to a day of the week (0 or 7 = Sunday)
+    pub fn day_of_week(mut self, day: u32) -> Self {
+        self.days_of_week = 1 << (day % 7);
+        self.any_day_of_week = false;
+        self
+    }
+
+    /// First run strictly after `after`
+    ///
+    /// Returns `None` only if no run falls within the next few years.
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        // Local times run backwards when clocks go back, so start early
+        // enough to revisit a repeated hour; runs before `after` are skipped
+        let start = (after + Duration::minutes(1))
+            .with_timezone(&self.timezone)
+            .naive_local()
+            - Duration::hours(2);
+        let mut from = start
+            .with_second(0)
+            .and_then(|t| t.with_nanosecond(0))
+            .unwrap_or(start);
+        let every_hour = self.hours == mask(0, 23);
+
+        loop {
+            let local = self.next_local(from)?;
+            let run = match self.timezone.from_local_datetime(&local) {
+                LocalResult::Single(at) => Some(at),
+                LocalResult::Ambiguous(earliest, latest) => {
+                    if earliest.with_timezone(&Utc) > after {
+                        Some(earliest)
+                    } else if every_hour {
+                        Some(latest)
+                    } else {
+                        None
+                    }
+                }
+                LocalResult::None if every_hour => None,
+                LocalResult::None => self.end_of_gap(local),
+            };
+            if let Some(run) = run.map(|at| at.with_timezone(&Utc)) {
+                if run > after {
+                    return Some(run);
+                }
+            }
+            from = local + Duration::minutes(1);
+        }
+    }
+
+    /// Next run after now
+    pub fn next_run_time(&self) -> DateTime<Utc> {
+        self.next_after(Utc::now())
+            .unwrap_or(DateTime::<Utc>::MAX_UTC)
+    }
+
+    /// First local time at or after `from` matching every field
+    fn next_local(&self, from: NaiveDateTime) -> Option<NaiveDateTime> {
+        let last_year = from.year() + SEARCH_YEARS;
+        let mut t = from;
+
+        while t.year() <= last_year {
+            let date = t.date();
+            if !has(self.months, date.month()) {
+                let (year, month) = if date.month() == 12 {
+                    (date.year() + 1, 1)
+                } else {
+                    (date.year(), date.month() + 1)
+                };
+                t = NaiveDate::from_ymd_opt(year, month, 1)?.and_hms_opt(0, 0, 0)?;
+            } else if !self.day_matches(date) {
+                t = date.succ_opt()?.and_hms_opt(0, 0, 0)?;
+            } else if !has(self.hours, t.hour()) {
+                t = date.and_hms_opt(t.hour(), 0, 0)? + Duration::hours(1);
+            } else if !has(self.minutes, t.minute()) {
+                t += Duration::minutes(1);
+            } else {
+                return Some(t);
+            }
+        }
+        None
+    }
+
+    fn day_matches(&self, date: NaiveDate) -> bool {
+        let day_of_month = has(self.days_of_month, date.day());
+        let day_of_week = has(self.days_of_week, date.weekday().num_days_from_sunday());
+        if self.any_day_of_month || self.any_day_of_week {
+            day_of_month && day_of_week
+        } else {
+            day_of_month || day_of_week
+        }
+    }
+
+    /// First instant after a local time skipped by a DST transition
+    fn end_of_gap(&self, skipped: NaiveDateTime) -> Option<DateTime<Tz>> {
+        // Transitions skip at most a few hours
+        (1..=240).find_map(|minutes| {
+            self.timezone
+                .from_local_datetime(&(skipped + Duration::minutes(minutes)))
+                .earliest()
+        })
+    }
+}
+
+impl Default for CronSchedule {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FromStr for CronSchedule {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for CronSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let field = |bits: u64, min: u32, max: u32, any: bool| {
+            if any || bits == mask(min, max) {
+                "*".to_string()
+            } else {
+                (min..=max)
+                    .filter(|v| has(bits, *v))
+                    .map(|v| v.to_string())
+                    .collect::<Vec<_>>()
+                    .join(",")
+            }
+        };
+        write!(
+            f,
+            "{} {} {} {} {}",
+            field(self.minutes, 0, 59, false),
+            field(self.hours, 0, 23, false),
+            field(self.days_of_month, 1, 31, self.any_day_of_month),
+            field(self.months, 1, 12, false),
+            field(self.days_of_week, 0, 6, self.any_day_of_week),
+        )
+    }
+}
+
+/// Parse an IANA timezone name
+pub fn parse_timezone(name: &str) -> Result<Tz> {
+    name.parse::<Tz>()
+        .map_err(|_| Error::invalid_input("timezone", format!("Unknown timezone '{}'", name)))
+}
+
+fn invalid(message: impl Into<String>) -> Error {
+    Error::invalid_input("expression", message)
+}
+
+/// `*` and `?` (with or without a step) leave a day field unrestricted
+fn is_wildcard(field: &str) -> bool {
+    field.starts_with('*') || field.starts_with('?')
+}
+
+fn mask(min: u32, max: u32) -> u64 {
+    (min..=max).fold(0, |bits, v| bits | (1 << v))
+}
+
+fn has(bits: u64, value: u32) -> bool {
+    bits & (1 << value) != 0
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    // The leap year 2000 keeps February 29 possible
+    let next = if month == 12 {
+        NaiveDate::from_ymd_opt(year + 1, 1, 1)
+    } else {
+        NaiveDate::from_ymd_opt(year, month + 1, 1)
+    };
+    next.and_then(|d| d.pred_opt()).map_or(31, |d| d.day())
+}
+
+fn parse_field(text: &str, field: &Field) -> Result<u64> {
+    let mut bits = 0;
+    for part in text.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 =
+                    step.parse().ok().filter(|s| *s > 0).ok_or_else(|| {
+                        invalid(format!("invalid step '{}' in {}", step, field.name))
+                    })?;
+                (range, Some(step))
+            }
+            None => (part, None),
+        };
+
+        let (start, end) = match range {
+            "*" | "?" => (field.min, field.max),
+            _ => match range.split_once('-') {
+                Some((start, end)) => (parse_value(start, field)?, parse_value(end, field)?),
+                // `5/15` runs from 5 to the end of the field
+                None if step.is_some() => (parse_value(range, field)?, field.max),
+                None => {
+                    let value = parse_value(range, field)?;
+                    (value, value)
+                }
+            },
+        };
+        if start > end {
+            return Err(invalid(format!(
+                "range {}-{} in {} is backwards",
+                start, end, field.name
+            )));
+        }
+
+        let step = step.unwrap_or(1) as usize;
+        for value in (start..=end).step_by(step) {
+            bits |= 1 << value;
+        }
+    }
+    Ok(bits)
+}
+
+fn parse_value(text: &str, field: &Field) -> Result<u32> {
+    let value = match field
+        .names
+        .iter()
+        .position(|name| name.eq_ignore_ascii_case(text))
+    {
+        Some(index) => index as u32 + if field.min == 1 { 1 } else { 0 },
+        None => text
+            .parse()
+            .map_err(|_| invalid(format!("invalid {} '{}'", field.name, text)))?,
+    };
+    if value < field.min || value > field.max {
+        return Err(invalid(format!(
+            "{} {} is outside {}-{}",
+            field.name, value, field.min, field.max
+        )));
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    fn next(expression: &str, timezone: &str, after: &str) -> DateTime<Utc> {
+        CronSchedule::parse_in(expression, timezone)
+            .unwrap()
+            .next_after(utc(after))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parse_syntax() {
+        let schedule = CronSchedule::parse("*/15 9-17 * JAN-mar MON-FRI").unwrap();
+        assert_eq!(
+            schedule.to_string(),
+            "0,15,30,45 9,10,11,12,13,14,15,16,17 * 1,2,3 1,2,3,4,5"
+        );
+
+        let schedule = CronSchedule::parse("5/20 0 1,15 * 7").unwrap();
+        assert_eq!(schedule.to_string(), "5,25,45 0 1,15 * 0");
+
+        assert_eq!(
+            CronSchedule::parse("@daily").unwrap(),
+            CronSchedule::parse("0 0 * * *").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for expression in [
+            "* * * *",
+            "60 * * * *",
+            "* 24 * * *",
+            "* * 0 * *",
+            "* * * 13 *",
+            "* * * * 8",
+            "*/0 * * * *",
+            "10-5 * * * *",
+            "* * * FOO *",
+            "0 0 30 2 *",
+            "@sometimes",
+        ] {
+            assert!(CronSchedule::parse(expression).is_err(), "{}", expression);
+        }
+        assert!(CronSchedule::parse_in("* * * * *", "Mars/Olympus").is_err());
+    }
+
+    #[test]
+    fn test_next_after() {
+        assert_eq!(
+            next("30 4 * * *", "UTC", "2024-03-01T04:30:00Z"),
+            utc("2024-03-02T04:30:00Z")
+        );
+        assert_eq!(
+            next("0 0 29 2 *", "UTC", "2024-03-01T00:00:00Z"),
+            utc("2028-02-29T00:00:00Z")
+        );
+        // Day of month or day of week when both are restricted
+        assert_eq!(
+            next("0 12 13 * FRI", "UTC", "2024-09-01T00:00:00Z"),
+            utc("2024-09-06T12:00:00Z")
+        );
+    }
+
+    #[test]
+    fn test_timezone() {
+        // 09:00 in New York is 13:00 UTC in summer and 14:00 in winter
+        assert_eq!(
+            next("0 9 * * *", "America/New_York", "2024-07-01T00:00:00Z"),
+            utc("2024-07-01T13:00:00Z")
+        );
+        assert_eq!(
+            next("0 9 * * *", "America/New_York", "2024-12-01T00:00:00Z"),
+            utc("2024-12-01T14:00:00Z")
+        );
+    }
+
+    #[test]
+    fn test_spring_forward() {
+        // Clocks in Berlin jump from 02:00 to 03:00 on 2024-03-31
+        assert_eq!(
+            next("30 2 * * *", "Europe/Berlin", "2024-03-30T12:00:00Z"),
+            utc("2024-03-31T01:00:00Z")
+        );
+        // Jobs running every hour skip the missing hour
+        assert_eq!(
+            next("30 * * * *", "Europe/Berlin", "2024-03-31T00:30:00Z"),
+            utc("2024-03-31T01:30:00Z")
+        );
+    }
+
+    #[test]
+    fn test_fall_back() {
+        // 02:00-03:00 happens twice in Berlin on 2024-10-27
+        let first = next("30 2 * * *", "Europe/Berlin", "2024-10-26T12:00:00Z");
+        assert_eq!(first, utc("2024-10-27T00:30:00Z"));
+        assert_eq!(
+            next("30 2 * * *", "Europe/Berlin", &first.to_rfc3339()),
+            utc("2024-10-28T01:30:00Z")
+        );
+
+        // Jobs running every hour run in both
+        let first = next("30 * * * *", "Europe/Berlin", "2024-10-27T00:00:00Z");
+        assert_eq!(first, utc("2024-10-27T00:30:00Z"));
+        assert_eq!(
+            next("30 * * * *", "Europe/Berlin", &first.to_rfc3339()),
+            utc("2024-10-27T01:30:00Z")
+        );
+    }
+}
@@ -0,0 +1,195 @@
+//! Dead-letter queue for jobs that ran out of attempts.
+//!
+//! The worker moves a job here once its retry policy gives up on it, or
+//! when no handler is registered for its type, so failures are kept for an
+//! administrator to inspect and then requeue or discard.
+
+use crate::job::{Job, JobStatus};
+use chrono::{DateTime, Utc};
+use rustpress_core::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// A job that ran out of attempts
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct DeadLetter {
+    /// Id of the job
+    pub id: Uuid,
+    pub tenant_id: Option<Uuid>,
+    pub queue: String,
+    pub job_type: String,
+    pub payload: serde_json::Value,
+    pub priority: i32,
+    pub attempts: i32,
+    pub max_attempts: i32,
+    /// Error of the last attempt
+    pub error: String,
+    /// When the job was first queued
+    pub job_created_at: DateTime<Utc>,
+    pub failed_at: DateTime<Utc>,
+}
+
+impl DeadLetter {
+    /// Dead letter for `job` failing with `error`
+    pub fn from_job(job: &Job, error: &str) -> Self {
+        Self {
+            id: job.id,
+            tenant_id: job.tenant_id,
+            queue: job.queue.clone(),
+            job_type: job.job_type.clone(),
+            payload: job.payload.clone(),
+            priority: job.priority,
+            attempts: job.attempts as i32,
+            max_attempts: job.max_attempts as i32,
+            error: error.to_string(),
+            job_created_at: job.created_at,
+            failed_at: Utc::now(),
+        }
+    }
+
+    /// The job, ready to run again with fresh attempts
+    pub fn into_job(self) -> Job {
+        let mut job = Job::from_raw(self.job_type, self.queue, self.payload);
+        job.id = self.id;
+        job.tenant_id = self.tenant_id;
+        job.priority = self.priority;
+        job.max_attempts = self.max_attempts.max(1) as u32;
+        job.last_error = Some(self.error);
+        job.created_at = self.job_created_at;
+        job.requeue();
+        debug_assert_eq!(job.status, JobStatus::Pending);
+        job
+    }
+}
+
+/// Filters for listing dead letters
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DeadLetterFilter {
+    pub queue: Option<String>,
+    pub job_type: Option<String>,
+}
+
+/// Dead letters kept in the `job_dead_letters` table
+pub struct DeadLetterQueue {
+    pool: PgPool,
+}
+
+impl DeadLetterQueue {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Dead letters matching `filter`, most recent failures first
+    pub async fn list(
+        &self,
+        filter: &DeadLetterFilter,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<DeadLetter>> {
+        sqlx::query_as(
+            r#"
+            SELECT * FROM job_dead_letters
+            WHERE ($1::text IS NULL OR queue = $1)
+              AND ($2::text IS NULL OR job_type = $2)
+            ORDER BY failed_at DESC, id
+            LIMIT $3 OFFSET $4
+            "#,
+        )
+        .bind(filter.queue.as_deref())
+        .bind(filter.job_type.as_deref())
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to list dead letters", e))
+    }
+
+    /// Number of dead letters matching `filter`
+    pub async fn count(&self, filter: &DeadLetterFilter) -> Result<u64> {
+        let (count,): (i64,) = sqlx::query_as(
+            r#"
+            SELECT COUNT(*) FROM job_dead_letters
+            WHERE ($1::text IS NULL OR queue = $1)
+              AND ($2::text IS NULL OR job_type = $2)
+            "#,
+        )
+        .bind(filter.queue.as_deref())
+        .bind(filter.job_type.as_deref())
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to count dead letters", e))?;
+
+        Ok(count as u64)
+    }
+
+    pub async fn get(&self, id: Uuid) -> Result<Option<DeadLetter>> {
+        sqlx::query_as("SELECT * FROM job_dead_letters WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to get dead letter", e))
+    }
+
+    /// Remove a dead letter to put it back in line, see
+    /// [`JobQueue::requeue_dead_letter`](crate::JobQueue::requeue_dead_letter)
+    pub async fn take(&self, id: Uuid) -> Result<Option<DeadLetter>> {
+        sqlx::query_as("DELETE FROM job_dead_letters WHERE id = $1 RETURNING *")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to take dead letter", e))
+    }
+
+    /// Store a dead letter, replacing one for the same job
+    pub async fn add(&self, letter: &DeadLetter) -> Result<()> {
+        crate::backend::insert_dead_letter(&self.pool, letter).await
+    }
+
+    /// Drop a dead letter for good; returns whether it existed
+    pub async fn discard(&self, id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM job_dead_letters WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to discard dead letter", e))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::job::jobs::SendEmailJob;
+
+    #[test]
+    fn test_dead_letter_round_trip() {
+        let mut job = Job::new(SendEmailJob {
+            to: "reader@example.com".to_string(),
+            subject: "Welcome".to_string(),
+            body: "Hello".to_string(),
+            html: false,
+        })
+        .with_priority(5);
+        for _ in 0..job.max_attempts {
+            job.reserve();
+        }
+
+        let letter = DeadLetter::from_job(&job, "SMTP connection refused");
+        assert_eq!(letter.attempts, 3);
+        assert_eq!(letter.error, "SMTP connection refused");
+
+        let requeued = letter.into_job();
+        assert_eq!(requeued.id, job.id);
+        assert_eq!(requeued.queue, "emails");
+        assert_eq!(requeued.priority, 5);
+        assert_eq!(requeued.attempts, 0);
+        assert_eq!(requeued.status, JobStatus::Pending);
+        assert!(requeued.can_retry());
+        assert_eq!(
+            requeued.payload::<SendEmailJob>().unwrap().to,
+            "reader@example.com"
+        );
+    }
+}
@@ -1,7 +1,8 @@
 //! Job handlers for RustPress background tasks.
 //!
 //! This module contains handlers for scheduled tasks like publishing
-//! scheduled posts and cleaning up expired theme previews.
+//! scheduled posts, unpublishing expired ones, withholding embargoed ones
+//! and cleaning up expired theme previews.
 
 use async_trait::async_trait;
 use rustpress_core::error::Result;
@@ -12,6 +13,10 @@ use uuid::Uuid;
 
 use crate::job::{JobHandler, JobPayload};
 
+/// SQL condition for a post inside its embargo window at `$1`
+const EMBARGO_ACTIVE: &str = "(embargo_ends_at IS NOT NULL AND embargo_ends_at > $1 \
+     AND (embargo_starts_at IS NULL OR embargo_starts_at <= $1))";
+
 /// Publish scheduled posts job - runs periodically to publish posts that are due
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PublishScheduledPostsJob {
@@ -57,33 +62,47 @@ impl JobHandler for PublishScheduledPostsHandler {
 
         let now = chrono::Utc::now();
 
-        // Find all posts that are scheduled and due for publication
-        let query = if let Some(site_id) = payload.site_id {
-            sqlx::query(
+        // Find all posts that are scheduled and due for publication; posts
+        // withheld by an embargo keep their original publish date
+        let embargoed = EMBARGO_ACTIVE;
+        let sql = if payload.site_id.is_some() {
+            format!(
                 r#"
                 UPDATE posts
-                SET status = 'published', published_at = $1, scheduled_at = NULL, updated_at = $1
+                SET status = 'published',
+                    published_at = CASE WHEN published_at IS NULL OR published_at > $1
+                                        THEN $1 ELSE published_at END,
+                    scheduled_at = NULL,
+                    updated_at = $1
                 WHERE status = 'scheduled'
                   AND scheduled_at <= $1
+                  AND NOT {embargoed}
                   AND site_id = $2
                 RETURNING id
                 "#,
             )
-            .bind(now)
-            .bind(site_id)
         } else {
-            sqlx::query(
+            format!(
                 r#"
                 UPDATE posts
-                SET status = 'published', published_at = $1, scheduled_at = NULL, updated_at = $1
+                SET status = 'published',
+                    published_at = CASE WHEN published_at IS NULL OR published_at > $1
+                                        THEN $1 ELSE published_at END,
+                    scheduled_at = NULL,
+                    updated_at = $1
                 WHERE status = 'scheduled'
                   AND scheduled_at <= $1
+                  AND NOT {embargoed}
                 RETURNING id
                 "#,
             )
-            .bind(now)
         };
 
+        let mut query = sqlx::query(&sql).bind(now);
+        if let Some(site_id) = payload.site_id {
+            query = query.bind(site_id);
+        }
+
         let result = query.execute(&self.pool).await.map_err(|e| {
             rustpress_core::error::Error::database(format!(
                 "Failed to publish scheduled posts: {}",
@@ -112,6 +131,207 @@ impl JobHandler for PublishScheduledPostsHandler {
     }
 }
 
+/// Unpublish expired posts job - returns published posts past their expiry
+/// date to draft
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnpublishExpiredPostsJob {
+    /// Optional site ID to limit scope (None = all sites)
+    pub site_id: Option<Uuid>,
+}
+
+impl JobPayload for UnpublishExpiredPostsJob {
+    fn job_type() -> &'static str {
+        "unpublish_expired_posts"
+    }
+
+    fn queue() -> &'static str {
+        "content"
+    }
+
+    fn max_attempts() -> u32 {
+        3
+    }
+
+    fn timeout_secs() -> u64 {
+        300 // 5 minutes
+    }
+}
+
+/// Handler for unpublishing expired posts
+pub struct UnpublishExpiredPostsHandler {
+    pool: PgPool,
+}
+
+impl UnpublishExpiredPostsHandler {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl JobHandler for UnpublishExpiredPostsHandler {
+    type Payload = UnpublishExpiredPostsJob;
+
+    async fn handle(&self, payload: Self::Payload) -> Result<()> {
+        info!(site_id = ?payload.site_id, "Unpublishing expired posts");
+
+        let now = chrono::Utc::now();
+
+        // The expiry is cleared so republishing doesn't expire the post again
+        let query = if let Some(site_id) = payload.site_id {
+            sqlx::query(
+                r#"
+                UPDATE posts
+                SET status = 'draft', expires_at = NULL, updated_at = $1
+                WHERE status = 'published'
+                  AND expires_at <= $1
+                  AND site_id = $2
+                "#,
+            )
+            .bind(now)
+            .bind(site_id)
+        } else {
+            sqlx::query(
+                r#"
+                UPDATE posts
+                SET status = 'draft', expires_at = NULL, updated_at = $1
+                WHERE status = 'published'
+                  AND expires_at <= $1
+                "#,
+            )
+            .bind(now)
+        };
+
+        let result = query.execute(&self.pool).await.map_err(|e| {
+            rustpress_core::error::Error::database(format!(
+                "Failed to unpublish expired posts: {}",
+                e
+            ))
+        })?;
+
+        let unpublished_count = result.rows_affected();
+        info!(unpublished_count, "Unpublished expired posts");
+
+        Ok(())
+    }
+
+    async fn failed(&self, payload: Self::Payload, error: &str) -> Result<()> {
+        error!(
+            site_id = ?payload.site_id,
+            error,
+            "Failed to unpublish expired posts"
+        );
+        Ok(())
+    }
+
+    async fn completed(&self, payload: Self::Payload) -> Result<()> {
+        info!(site_id = ?payload.site_id, "Completed expired posts unpublishing job");
+        Ok(())
+    }
+}
+
+/// Enforce post embargoes job - withholds published posts whose embargo has
+/// started by scheduling them for the embargo's end, when
+/// [`PublishScheduledPostsJob`] publishes them again
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnforcePostEmbargoesJob {
+    /// Optional site ID to limit scope (None = all sites)
+    pub site_id: Option<Uuid>,
+}
+
+impl JobPayload for EnforcePostEmbargoesJob {
+    fn job_type() -> &'static str {
+        "enforce_post_embargoes"
+    }
+
+    fn queue() -> &'static str {
+        "content"
+    }
+
+    fn max_attempts() -> u32 {
+        3
+    }
+
+    fn timeout_secs() -> u64 {
+        300 // 5 minutes
+    }
+}
+
+/// Handler for enforcing post embargoes
+pub struct EnforcePostEmbargoesHandler {
+    pool: PgPool,
+}
+
+impl EnforcePostEmbargoesHandler {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl JobHandler for EnforcePostEmbargoesHandler {
+    type Payload = EnforcePostEmbargoesJob;
+
+    async fn handle(&self, payload: Self::Payload) -> Result<()> {
+        info!(site_id = ?payload.site_id, "Enforcing post embargoes");
+
+        let now = chrono::Utc::now();
+        let embargoed = EMBARGO_ACTIVE;
+
+        let sql = if payload.site_id.is_some() {
+            format!(
+                r#"
+                UPDATE posts
+                SET status = 'scheduled', scheduled_at = embargo_ends_at, updated_at = $1
+                WHERE status = 'published'
+                  AND {embargoed}
+                  AND site_id = $2
+                "#,
+            )
+        } else {
+            format!(
+                r#"
+                UPDATE posts
+                SET status = 'scheduled', scheduled_at = embargo_ends_at, updated_at = $1
+                WHERE status = 'published'
+                  AND {embargoed}
+                "#,
+            )
+        };
+
+        let mut query = sqlx::query(&sql).bind(now);
+        if let Some(site_id) = payload.site_id {
+            query = query.bind(site_id);
+        }
+
+        let result = query.execute(&self.pool).await.map_err(|e| {
+            rustpress_core::error::Error::database(format!(
+                "Failed to enforce post embargoes: {}",
+                e
+            ))
+        })?;
+
+        let withheld_count = result.rows_affected();
+        info!(withheld_count, "Withheld embargoed posts");
+
+        Ok(())
+    }
+
+    async fn failed(&self, payload: Self::Payload, error: &str) -> Result<()> {
+        error!(
+            site_id = ?payload.site_id,
+            error,
+            "Failed to enforce post embargoes"
+        );
+        Ok(())
+    }
+
+    async fn completed(&self, payload: Self::Payload) -> Result<()> {
+        info!(site_id = ?payload.site_id, "Completed post embargo job");
+        Ok(())
+    }
+}
+
 /// Clean expired theme previews job - removes stale preview sessions
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CleanThemePreviewsJob {
@@ -212,6 +432,20 @@ mod tests {
         assert_eq!(PublishScheduledPostsJob::queue(), "content");
     }
 
+    #[test]
+    fn test_post_schedule_job_types() {
+        assert_eq!(
+            UnpublishExpiredPostsJob::job_type(),
+            "unpublish_expired_posts"
+        );
+        assert_eq!(UnpublishExpiredPostsJob::queue(), "content");
+        assert_eq!(
+            EnforcePostEmbargoesJob::job_type(),
+            "enforce_post_embargoes"
+        );
+        assert_eq!(EnforcePostEmbargoesJob::queue(), "content");
+    }
+
     #[test]
     fn test_clean_theme_previews_job_type() {
         assert_eq!(CleanThemePreviewsJob::job_type(), "clean_theme_previews");
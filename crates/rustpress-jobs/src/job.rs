@@ -1,5 +1,6 @@
 //! Job definitions and traits.
 
+use crate::retry::{Backoff, RetryPolicy};
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use rustpress_core::error::Result;
@@ -26,6 +27,33 @@ impl Default for JobStatus {
     }
 }
 
+impl JobStatus {
+    /// Name stored in the `jobs.status` column
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Reserved => "reserved",
+            Self::Processing => "processing",
+            Self::Completed => "completed",
+            Self::Failed => "failed",
+            Self::Cancelled => "cancelled",
+        }
+    }
+
+    /// Status stored as `name`, if it is one
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "pending" => Some(Self::Pending),
+            "reserved" => Some(Self::Reserved),
+            "processing" => Some(Self::Processing),
+            "completed" => Some(Self::Completed),
+            "failed" => Some(Self::Failed),
+            "cancelled" => Some(Self::Cancelled),
+            _ => None,
+        }
+    }
+}
+
 /// Job payload trait for type-safe job data
 pub trait JobPayload: Serialize + DeserializeOwned + Send + Sync + 'static {
     /// Unique job type identifier
@@ -41,6 +69,12 @@ pub trait JobPayload: Serialize + DeserializeOwned + Send + Sync + 'static {
         3
     }
 
+    /// How failed attempts are retried; jobs out of attempts go to the
+    /// dead-letter queue
+    fn retry_policy() -> RetryPolicy {
+        RetryPolicy::new(Self::max_attempts())
+    }
+
     /// Timeout in seconds
     fn timeout_secs() -> u64 {
         300
@@ -104,7 +138,7 @@ impl Job {
             status: JobStatus::Pending,
             priority: 0,
             attempts: 0,
-            max_attempts: P::max_attempts(),
+            max_attempts: P::retry_policy().max_attempts,
             timeout_secs: P::timeout_secs(),
             last_error: None,
             available_at: Utc::now() + chrono::Duration::seconds(P::delay_secs() as i64),
@@ -128,6 +162,33 @@ impl Job {
         job
     }
 
+    /// Create a job from an already serialized payload, with the default
+    /// attempt and timeout limits
+    pub fn from_raw(
+        job_type: impl Into<String>,
+        queue: impl Into<String>,
+        payload: serde_json::Value,
+    ) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::now_v7(),
+            tenant_id: None,
+            queue: queue.into(),
+            job_type: job_type.into(),
+            payload,
+            status: JobStatus::Pending,
+            priority: 0,
+            attempts: 0,
+            max_attempts: 3,
+            timeout_secs: 300,
+            last_error: None,
+            available_at: now,
+            reserved_at: None,
+            completed_at: None,
+            created_at: now,
+        }
+    }
+
     /// Set tenant ID
     pub fn with_tenant(mut self, tenant_id: Uuid) -> Self {
         self.tenant_id = Some(tenant_id);
@@ -157,6 +218,16 @@ impl Job {
         self.attempts < self.max_attempts
     }
 
+    /// Put a job out of the dead-letter queue back in line with fresh
+    /// attempts
+    pub fn requeue(&mut self) {
+        self.status = JobStatus::Pending;
+        self.attempts = 0;
+        self.reserved_at = None;
+        self.completed_at = None;
+        self.available_at = Utc::now();
+    }
+
     /// Check if job has timed out
     pub fn has_timed_out(&self) -> bool {
         if let Some(reserved_at) = self.reserved_at {
@@ -231,6 +302,14 @@ pub mod jobs {
         fn queue() -> &'static str {
             "emails"
         }
+
+        /// SMTP failures are usually transient, so retry sooner
+        fn retry_policy() -> RetryPolicy {
+            RetryPolicy::new(Self::max_attempts()).with_backoff(Backoff::Exponential {
+                base_secs: 30,
+                max_secs: 900,
+            })
+        }
     }
 
     /// Process webhook job
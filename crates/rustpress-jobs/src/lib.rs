@@ -2,17 +2,40 @@
 //!
 //! Background job queue system for asynchronous task processing.
 
+pub mod audit;
+pub mod backend;
+pub mod cron;
+pub mod dead_letter;
 pub mod handlers;
 pub mod job;
 pub mod queue;
+pub mod retry;
+pub mod saga;
 pub mod scheduler;
+pub mod sla;
 pub mod worker;
 
+pub use audit::{ExecutionAudit, ExecutionOutcome, ExecutionRecord, JobExecution, JobTypeStats};
+#[cfg(feature = "redis")]
+pub use backend::RedisBackend;
+pub use backend::{PostgresBackend, QueueBackend};
+pub use cron::CronSchedule;
+pub use dead_letter::{DeadLetter, DeadLetterFilter, DeadLetterQueue};
 pub use handlers::{
-    CleanThemePreviewsHandler, CleanThemePreviewsJob, PublishScheduledPostsHandler,
-    PublishScheduledPostsJob,
+    CleanThemePreviewsHandler, CleanThemePreviewsJob, EnforcePostEmbargoesHandler,
+    EnforcePostEmbargoesJob, PublishScheduledPostsHandler, PublishScheduledPostsJob,
+    UnpublishExpiredPostsHandler, UnpublishExpiredPostsJob,
 };
 pub use job::{Job, JobHandler, JobPayload, JobStatus};
 pub use queue::{JobQueue, QueueConfig};
-pub use scheduler::{Schedule, Scheduler};
-pub use worker::{Worker, WorkerPool};
+pub use retry::{Backoff, RetryPolicy};
+pub use saga::{SagaContext, SagaCoordinator, SagaDefinition, SagaStatus, SagaStep};
+pub use scheduler::{
+    CatchUp, InMemoryScheduleStore, PgScheduleStore, Schedule, ScheduleDefinition, ScheduleStore,
+    Scheduler,
+};
+pub use sla::{
+    EvaluateJobSlasHandler, EvaluateJobSlasJob, JobHealth, JobSla, JobTypeHealth, SlaBreach,
+    SlaMetric, SlaMonitor, SlaStore,
+};
+pub use worker::{PauseSwitch, Worker, WorkerConfig, WorkerPool};
@@ -1,12 +1,16 @@
 //! Job queue implementation.
 
-use crate::job::{Job, JobPayload, JobStatus};
+use crate::backend::{PostgresBackend, QueueBackend};
+use crate::dead_letter::DeadLetterQueue;
+use crate::job::{Job, JobPayload};
+use crate::sla::SlaMonitor;
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
-use rustpress_core::error::{Error, Result};
+use rustpress_core::error::Result;
+use rustpress_core::tenant::current_site;
 use sqlx::PgPool;
-#[allow(unused_imports)]
 use std::sync::Arc;
+use std::time::Duration;
 use uuid::Uuid;
 
 /// Queue configuration
@@ -22,6 +26,11 @@ pub struct QueueConfig {
     pub batch_size: u32,
     /// Sleep duration when queue is empty (milliseconds)
     pub sleep_on_empty_ms: u64,
+    /// How long a reserved job stays leased without a heartbeat before
+    /// other workers may take it over (seconds)
+    pub visibility_timeout: u64,
+    /// How often a worker renews the lease on a running job (seconds)
+    pub heartbeat_interval: u64,
 }
 
 impl Default for QueueConfig {
@@ -32,6 +41,8 @@ impl Default for QueueConfig {
             retry_delay: 60,
             batch_size: 10,
             sleep_on_empty_ms: 1000,
+            visibility_timeout: 300,
+            heartbeat_interval: 60,
         }
     }
 }
@@ -48,17 +59,27 @@ pub trait Queue: Send + Sync {
     /// Get next available job from queue
     async fn pop(&self, queue: &str) -> Result<Option<Job>>;
 
+    /// Renew the lease on a reserved job; `false` when another worker has
+    /// taken it over
+    async fn heartbeat(&self, job: &Job) -> Result<bool>;
+
     /// Get multiple jobs from queue
     async fn pop_batch(&self, queue: &str, count: u32) -> Result<Vec<Job>>;
 
-    /// Mark job as completed
-    async fn complete(&self, job_id: Uuid) -> Result<()>;
+    /// Mark a reserved job as completed
+    async fn complete(&self, job: &Job) -> Result<()>;
+
+    /// Mark a reserved job as failed
+    async fn fail(&self, job: &Job, error: &str) -> Result<()>;
 
-    /// Mark job as failed
-    async fn fail(&self, job_id: Uuid, error: &str) -> Result<()>;
+    /// Release a reserved job back to queue
+    async fn release(&self, job: &Job, delay_secs: u64) -> Result<()>;
 
-    /// Release job back to queue
-    async fn release(&self, job_id: Uuid, delay_secs: u64) -> Result<()>;
+    /// Release a failed job back to queue, keeping its error
+    async fn retry(&self, job: &Job, delay_secs: u64, error: &str) -> Result<()>;
+
+    /// Move a job that ran out of attempts to the dead-letter queue
+    async fn bury(&self, job: &Job, error: &str) -> Result<()>;
 
     /// Delete a job
     async fn delete(&self, job_id: Uuid) -> Result<()>;
@@ -79,40 +100,92 @@ pub trait Queue: Send + Sync {
     async fn release_stale(&self, older_than_secs: u64) -> Result<u64>;
 }
 
-/// Database-backed job queue
+/// Job queue shared by every worker, stored in a [`QueueBackend`]
 pub struct JobQueue {
     pool: PgPool,
-    #[allow(dead_code)]
+    backend: Arc<dyn QueueBackend>,
     config: QueueConfig,
     tenant_id: Option<Uuid>,
 }
 
 impl JobQueue {
     pub fn new(pool: PgPool) -> Self {
-        Self {
-            pool,
-            config: QueueConfig::default(),
-            tenant_id: None,
-        }
+        Self::with_config(pool, QueueConfig::default())
     }
 
     pub fn with_config(pool: PgPool, config: QueueConfig) -> Self {
         Self {
+            backend: Arc::new(PostgresBackend::new(pool.clone())),
             pool,
             config,
             tenant_id: None,
         }
     }
 
+    /// Keep jobs in `backend` instead of the `jobs` table
+    pub fn with_backend(mut self, backend: Arc<dyn QueueBackend>) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// Tenant of dispatched jobs; without one, jobs belong to the site
+    /// being served
     pub fn with_tenant(mut self, tenant_id: Uuid) -> Self {
         self.tenant_id = Some(tenant_id);
         self
     }
 
+    /// Name of the backend jobs are stored in
+    pub fn backend_name(&self) -> &'static str {
+        self.backend.name()
+    }
+
+    pub fn config(&self) -> &QueueConfig {
+        &self.config
+    }
+
+    /// How long a reserved job stays leased without a heartbeat
+    pub fn visibility_timeout(&self) -> Duration {
+        Duration::from_secs(self.config.visibility_timeout.max(1))
+    }
+
+    /// How often workers renew the lease on running jobs
+    pub fn heartbeat_interval(&self) -> Duration {
+        Duration::from_secs(self.config.heartbeat_interval.max(1))
+    }
+
+    /// Jobs that ran out of attempts
+    pub fn dead_letters(&self) -> DeadLetterQueue {
+        DeadLetterQueue::new(self.pool.clone())
+    }
+
+    /// Execution history and the SLAs it is checked against
+    pub fn sla_monitor(&self) -> SlaMonitor {
+        SlaMonitor::new(self.pool.clone())
+    }
+
+    /// Put a dead letter back on its queue with fresh attempts; returns
+    /// whether it existed
+    pub async fn requeue_dead_letter(&self, id: Uuid) -> Result<bool> {
+        let dead_letters = self.dead_letters();
+        let Some(letter) = dead_letters.take(id).await? else {
+            return Ok(false);
+        };
+
+        if let Err(e) = self.push(letter.clone().into_job()).await {
+            // Keep the dead letter rather than lose the job
+            dead_letters.add(&letter).await?;
+            return Err(e);
+        }
+
+        tracing::info!(job_id = %id, queue = %letter.queue, "Dead letter requeued");
+        Ok(true)
+    }
+
     /// Dispatch a job
     pub async fn dispatch<P: JobPayload>(&self, payload: P) -> Result<Uuid> {
         let mut job = Job::new(payload);
-        if let Some(tenant_id) = self.tenant_id {
+        if let Some(tenant_id) = self.tenant_id.or_else(current_site) {
             job = job.with_tenant(tenant_id);
         }
         self.push(job).await
@@ -125,7 +198,7 @@ impl JobQueue {
         delay_secs: u64,
     ) -> Result<Uuid> {
         let mut job = Job::new(payload).delay(delay_secs);
-        if let Some(tenant_id) = self.tenant_id {
+        if let Some(tenant_id) = self.tenant_id.or_else(current_site) {
             job = job.with_tenant(tenant_id);
         }
         self.push(job).await
@@ -138,7 +211,7 @@ impl JobQueue {
         time: DateTime<Utc>,
     ) -> Result<Uuid> {
         let mut job = Job::new(payload).schedule_at(time);
-        if let Some(tenant_id) = self.tenant_id {
+        if let Some(tenant_id) = self.tenant_id.or_else(current_site) {
             job = job.with_tenant(tenant_id);
         }
         self.push(job).await
@@ -148,31 +221,10 @@ impl JobQueue {
 #[async_trait]
 impl Queue for JobQueue {
     async fn push(&self, job: Job) -> Result<Uuid> {
-        let id = job.id;
-
-        sqlx::query(
-            r#"
-            INSERT INTO jobs (id, tenant_id, queue, job_type, payload, status, priority, attempts, max_attempts, available_at, created_at)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
-            "#,
-        )
-        .bind(job.id)
-        .bind(job.tenant_id)
-        .bind(&job.queue)
-        .bind(&job.job_type)
-        .bind(&job.payload)
-        .bind(serde_json::to_string(&job.status).unwrap_or_default())
-        .bind(job.priority)
-        .bind(job.attempts as i32)
-        .bind(job.max_attempts as i32)
-        .bind(job.available_at)
-        .bind(job.created_at)
-        .execute(&self.pool)
-        .await
-        .map_err(|e| Error::database_with_source("Failed to push job", e))?;
-
-        tracing::debug!(job_id = %id, queue = %job.queue, "Job pushed to queue");
-        Ok(id)
+        self.backend.push(&job).await?;
+
+        tracing::debug!(job_id = %job.id, queue = %job.queue, "Job pushed to queue");
+        Ok(job.id)
     }
 
     async fn push_delayed(&self, mut job: Job, delay_secs: u64) -> Result<Uuid> {
@@ -181,37 +233,13 @@ impl Queue for JobQueue {
     }
 
     async fn pop(&self, queue: &str) -> Result<Option<Job>> {
-        let tenant_condition = match self.tenant_id {
-            Some(id) => format!("AND tenant_id = '{}'", id),
-            None => String::new(),
-        };
-
-        let query = format!(
-            r#"
-            UPDATE jobs
-            SET status = 'reserved', reserved_at = NOW(), attempts = attempts + 1
-            WHERE id = (
-                SELECT id FROM jobs
-                WHERE queue = $1
-                AND status = 'pending'
-                AND available_at <= NOW()
-                {}
-                ORDER BY priority DESC, available_at ASC
-                FOR UPDATE SKIP LOCKED
-                LIMIT 1
-            )
-            RETURNING id, tenant_id, queue, job_type, payload, status, priority, attempts, max_attempts, last_error, available_at, reserved_at, completed_at, created_at
-            "#,
-            tenant_condition
-        );
-
-        let job: Option<JobRow> = sqlx::query_as(&query)
-            .bind(queue)
-            .fetch_optional(&self.pool)
+        self.backend
+            .reserve(queue, self.tenant_id, self.visibility_timeout())
             .await
-            .map_err(|e| Error::database_with_source("Failed to pop job", e))?;
+    }
 
-        Ok(job.map(|r| r.into()))
+    async fn heartbeat(&self, job: &Job) -> Result<bool> {
+        self.backend.heartbeat(job).await
     }
 
     async fn pop_batch(&self, queue: &str, count: u32) -> Result<Vec<Job>> {
@@ -225,164 +253,67 @@ impl Queue for JobQueue {
         Ok(jobs)
     }
 
-    async fn complete(&self, job_id: Uuid) -> Result<()> {
-        sqlx::query("UPDATE jobs SET status = 'completed', completed_at = NOW() WHERE id = $1")
-            .bind(job_id)
-            .execute(&self.pool)
-            .await
-            .map_err(|e| Error::database_with_source("Failed to complete job", e))?;
+    async fn complete(&self, job: &Job) -> Result<()> {
+        self.backend.complete(job).await?;
 
-        tracing::debug!(job_id = %job_id, "Job completed");
+        tracing::debug!(job_id = %job.id, "Job completed");
         Ok(())
     }
 
-    async fn fail(&self, job_id: Uuid, error: &str) -> Result<()> {
-        sqlx::query(
-            "UPDATE jobs SET status = 'failed', last_error = $2, reserved_at = NULL WHERE id = $1",
-        )
-        .bind(job_id)
-        .bind(error)
-        .execute(&self.pool)
-        .await
-        .map_err(|e| Error::database_with_source("Failed to fail job", e))?;
-
-        tracing::debug!(job_id = %job_id, error = %error, "Job failed");
+    async fn fail(&self, job: &Job, error: &str) -> Result<()> {
+        self.backend.fail(job, error).await?;
+
+        tracing::debug!(job_id = %job.id, error = %error, "Job failed");
         Ok(())
     }
 
-    async fn release(&self, job_id: Uuid, delay_secs: u64) -> Result<()> {
-        let available_at = Utc::now() + chrono::Duration::seconds(delay_secs as i64);
+    async fn release(&self, job: &Job, delay_secs: u64) -> Result<()> {
+        self.backend.release(job, delay_secs, None).await?;
 
-        sqlx::query(
-            "UPDATE jobs SET status = 'pending', reserved_at = NULL, available_at = $2 WHERE id = $1",
-        )
-        .bind(job_id)
-        .bind(available_at)
-        .execute(&self.pool)
-        .await
-        .map_err(|e| Error::database_with_source("Failed to release job", e))?;
+        tracing::debug!(job_id = %job.id, delay_secs = delay_secs, "Job released");
+        Ok(())
+    }
+
+    async fn retry(&self, job: &Job, delay_secs: u64, error: &str) -> Result<()> {
+        self.backend.release(job, delay_secs, Some(error)).await?;
 
-        tracing::debug!(job_id = %job_id, delay_secs = delay_secs, "Job released");
+        tracing::debug!(job_id = %job.id, delay_secs = delay_secs, error = %error, "Job scheduled for retry");
         Ok(())
     }
 
-    async fn delete(&self, job_id: Uuid) -> Result<()> {
-        sqlx::query("DELETE FROM jobs WHERE id = $1")
-            .bind(job_id)
-            .execute(&self.pool)
-            .await
-            .map_err(|e| Error::database_with_source("Failed to delete job", e))?;
+    async fn bury(&self, job: &Job, error: &str) -> Result<()> {
+        self.backend.bury(job, error).await?;
 
+        tracing::warn!(job_id = %job.id, queue = %job.queue, error = %error, "Job moved to dead-letter queue");
         Ok(())
     }
 
-    async fn get(&self, job_id: Uuid) -> Result<Option<Job>> {
-        let job: Option<JobRow> = sqlx::query_as("SELECT * FROM jobs WHERE id = $1")
-            .bind(job_id)
-            .fetch_optional(&self.pool)
-            .await
-            .map_err(|e| Error::database_with_source("Failed to get job", e))?;
+    async fn delete(&self, job_id: Uuid) -> Result<()> {
+        self.backend.delete(job_id).await
+    }
 
-        Ok(job.map(|r| r.into()))
+    async fn get(&self, job_id: Uuid) -> Result<Option<Job>> {
+        self.backend.get(job_id).await
     }
 
     async fn size(&self, queue: &str) -> Result<u64> {
-        let (count,): (i64,) =
-            sqlx::query_as("SELECT COUNT(*) FROM jobs WHERE queue = $1 AND status = 'pending'")
-                .bind(queue)
-                .fetch_one(&self.pool)
-                .await
-                .map_err(|e| Error::database_with_source("Failed to get queue size", e))?;
-
-        Ok(count as u64)
+        self.backend.size(queue).await
     }
 
     async fn clear(&self, queue: &str) -> Result<u64> {
-        let result = sqlx::query("DELETE FROM jobs WHERE queue = $1")
-            .bind(queue)
-            .execute(&self.pool)
-            .await
-            .map_err(|e| Error::database_with_source("Failed to clear queue", e))?;
-
-        Ok(result.rows_affected())
+        self.backend.clear(queue).await
     }
 
     async fn retry_failed(&self, queue: &str) -> Result<u64> {
-        let result = sqlx::query(
-            r#"
-            UPDATE jobs
-            SET status = 'pending', reserved_at = NULL, available_at = NOW()
-            WHERE queue = $1 AND status = 'failed' AND attempts < max_attempts
-            "#,
-        )
-        .bind(queue)
-        .execute(&self.pool)
-        .await
-        .map_err(|e| Error::database_with_source("Failed to retry failed jobs", e))?;
-
-        Ok(result.rows_affected())
+        self.backend.retry_failed(queue).await
     }
 
     async fn release_stale(&self, older_than_secs: u64) -> Result<u64> {
-        let threshold = Utc::now() - chrono::Duration::seconds(older_than_secs as i64);
-
-        let result = sqlx::query(
-            r#"
-            UPDATE jobs
-            SET status = 'pending', reserved_at = NULL
-            WHERE status = 'reserved' AND reserved_at < $1
-            "#,
-        )
-        .bind(threshold)
-        .execute(&self.pool)
-        .await
-        .map_err(|e| Error::database_with_source("Failed to release stale jobs", e))?;
-
-        if result.rows_affected() > 0 {
-            tracing::info!(count = result.rows_affected(), "Released stale jobs");
+        let released = self.backend.release_stale(older_than_secs).await?;
+        if released > 0 {
+            tracing::info!(count = released, "Released stale jobs");
         }
 
-        Ok(result.rows_affected())
-    }
-}
-
-/// Database row for jobs
-#[derive(sqlx::FromRow)]
-struct JobRow {
-    id: Uuid,
-    tenant_id: Option<Uuid>,
-    queue: String,
-    job_type: String,
-    payload: serde_json::Value,
-    status: String,
-    priority: Option<i32>,
-    attempts: Option<i32>,
-    max_attempts: Option<i32>,
-    last_error: Option<String>,
-    available_at: DateTime<Utc>,
-    reserved_at: Option<DateTime<Utc>>,
-    completed_at: Option<DateTime<Utc>>,
-    created_at: DateTime<Utc>,
-}
-
-impl From<JobRow> for Job {
-    fn from(row: JobRow) -> Self {
-        Job {
-            id: row.id,
-            tenant_id: row.tenant_id,
-            queue: row.queue,
-            job_type: row.job_type,
-            payload: row.payload,
-            status: serde_json::from_str(&row.status).unwrap_or(JobStatus::Pending),
-            priority: row.priority.unwrap_or(0),
-            attempts: row.attempts.unwrap_or(0) as u32,
-            max_attempts: row.max_attempts.unwrap_or(3) as u32,
-            timeout_secs: 300,
-            last_error: row.last_error,
-            available_at: row.available_at,
-            reserved_at: row.reserved_at,
-            completed_at: row.completed_at,
-            created_at: row.created_at,
-        }
+        Ok(released)
     }
 }
@@ -0,0 +1,165 @@
+//! Retry policies for failed jobs.
+//!
+//! A job type's [`RetryPolicy`] decides how many times it runs and how long
+//! the worker waits between attempts. Jobs that run out of attempts are
+//! moved to the dead-letter queue instead of being dropped.
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// How the wait between attempts grows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Backoff {
+    /// The same wait before every retry
+    Fixed { delay_secs: u64 },
+    /// `delay_secs` times the number of failed attempts
+    Linear { delay_secs: u64, max_secs: u64 },
+    /// `base_secs` doubled after every failed attempt
+    Exponential { base_secs: u64, max_secs: u64 },
+}
+
+impl Backoff {
+    /// Wait after `failed_attempts` attempts have failed, before jitter
+    pub fn delay(&self, failed_attempts: u32) -> Duration {
+        let n = failed_attempts.max(1);
+        let secs = match *self {
+            Self::Fixed { delay_secs } => delay_secs,
+            Self::Linear {
+                delay_secs,
+                max_secs,
+            } => delay_secs.saturating_mul(n as u64).min(max_secs),
+            Self::Exponential {
+                base_secs,
+                max_secs,
+            } => {
+                let factor = 2u64.checked_pow(n - 1).unwrap_or(u64::MAX);
+                base_secs.saturating_mul(factor).min(max_secs)
+            }
+        };
+        Duration::from_secs(secs)
+    }
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self::Exponential {
+            base_secs: 60,
+            max_secs: 3600,
+        }
+    }
+}
+
+/// How often and how far apart a job is attempted
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    /// Attempts including the first one
+    pub max_attempts: u32,
+    pub backoff: Backoff,
+    /// Share of each delay, 0 to 1, that is randomized so jobs failing
+    /// together do not retry together
+    pub jitter: f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            backoff: Backoff::default(),
+            jitter: 0.2,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// `max_attempts` attempts with the default backoff
+    pub fn new(max_attempts: u32) -> Self {
+        Self {
+            max_attempts: max_attempts.max(1),
+            ..Self::default()
+        }
+    }
+
+    /// Run once and never retry
+    pub fn none() -> Self {
+        Self::new(1)
+    }
+
+    pub fn with_backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    pub fn with_jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Whether a job that has made `attempts` attempts may run again
+    pub fn should_retry(&self, attempts: u32) -> bool {
+        attempts < self.max_attempts
+    }
+
+    /// Wait before the next attempt after `failed_attempts` failures, with
+    /// jitter applied
+    pub fn delay(&self, failed_attempts: u32) -> Duration {
+        let delay = self.backoff.delay(failed_attempts);
+        if self.jitter <= 0.0 || delay.is_zero() {
+            return delay;
+        }
+        // Spread the delay over [delay * (1 - jitter), delay]
+        let factor = 1.0 - rand::thread_rng().gen_range(0.0..=self.jitter);
+        delay.mul_f64(factor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_delays() {
+        let exponential = Backoff::Exponential {
+            base_secs: 10,
+            max_secs: 100,
+        };
+        assert_eq!(exponential.delay(1), Duration::from_secs(10));
+        assert_eq!(exponential.delay(3), Duration::from_secs(40));
+        assert_eq!(exponential.delay(5), Duration::from_secs(100));
+        assert_eq!(exponential.delay(200), Duration::from_secs(100));
+
+        let linear = Backoff::Linear {
+            delay_secs: 30,
+            max_secs: 75,
+        };
+        assert_eq!(linear.delay(2), Duration::from_secs(60));
+        assert_eq!(linear.delay(3), Duration::from_secs(75));
+
+        let fixed = Backoff::Fixed { delay_secs: 5 };
+        assert_eq!(fixed.delay(9), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_jitter_stays_within_bounds() {
+        let policy = RetryPolicy::new(5)
+            .with_backoff(Backoff::Fixed { delay_secs: 100 })
+            .with_jitter(0.5);
+        for _ in 0..100 {
+            let delay = policy.delay(1);
+            assert!(delay >= Duration::from_secs(50) && delay <= Duration::from_secs(100));
+        }
+
+        let exact = policy.with_jitter(0.0);
+        assert_eq!(exact.delay(1), Duration::from_secs(100));
+    }
+
+    #[test]
+    fn test_should_retry() {
+        let policy = RetryPolicy::new(3);
+        assert!(policy.should_retry(1));
+        assert!(policy.should_retry(2));
+        assert!(!policy.should_retry(3));
+        assert!(!RetryPolicy::none().should_retry(1));
+    }
+}
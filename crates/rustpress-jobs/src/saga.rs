@@ -0,0 +1,778 @@
+//! Saga coordinator for multi-step operations spanning services.
+//!
+//! A saga is an ordered list of steps, each paired with a compensating
+//! action. Steps run in order; if one fails, the already-completed steps
+//! are compensated in reverse order. Saga state is persisted after every
+//! transition so that a coordinator can resume in-flight sagas after a
+//! crash. Steps must therefore be idempotent.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use parking_lot::RwLock;
+use rustpress_core::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Saga status
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SagaStatus {
+    /// Steps are executing
+    Running,
+    /// All steps completed
+    Completed,
+    /// A step failed and completed steps are being compensated
+    Compensating,
+    /// All completed steps were compensated
+    Compensated,
+    /// Compensation failed; manual intervention required
+    Failed,
+}
+
+impl SagaStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Running => "running",
+            Self::Completed => "completed",
+            Self::Compensating => "compensating",
+            Self::Compensated => "compensated",
+            Self::Failed => "failed",
+        }
+    }
+
+    /// Whether the saga still has work to do
+    pub fn is_active(&self) -> bool {
+        matches!(self, Self::Running | Self::Compensating)
+    }
+}
+
+impl std::str::FromStr for SagaStatus {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "running" => Ok(Self::Running),
+            "completed" => Ok(Self::Completed),
+            "compensating" => Ok(Self::Compensating),
+            "compensated" => Ok(Self::Compensated),
+            "failed" => Ok(Self::Failed),
+            other => Err(Error::invalid_input(
+                "status",
+                format!("Unknown saga status: {}", other),
+            )),
+        }
+    }
+}
+
+/// Shared data passed between saga steps and persisted with the saga
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SagaContext {
+    pub data: HashMap<String, serde_json::Value>,
+}
+
+impl SagaContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, key: impl Into<String>, value: serde_json::Value) -> Self {
+        self.data.insert(key.into(), value);
+        self
+    }
+
+    pub fn get(&self, key: &str) -> Option<&serde_json::Value> {
+        self.data.get(key)
+    }
+
+    pub fn set(&mut self, key: impl Into<String>, value: serde_json::Value) {
+        self.data.insert(key.into(), value);
+    }
+}
+
+/// A single saga step with its compensating action
+#[async_trait]
+pub trait SagaStep: Send + Sync {
+    /// Step name, unique within a saga definition
+    fn name(&self) -> &str;
+
+    /// Perform the step; may record outputs in the context for later steps
+    async fn execute(&self, ctx: &mut SagaContext) -> Result<()>;
+
+    /// Undo the step after a later step failed
+    async fn compensate(&self, _ctx: &SagaContext) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// A named, ordered list of saga steps
+pub struct SagaDefinition {
+    pub saga_type: String,
+    steps: Vec<Arc<dyn SagaStep>>,
+}
+
+impl SagaDefinition {
+    pub fn new(saga_type: impl Into<String>) -> Self {
+        Self {
+            saga_type: saga_type.into(),
+            steps: Vec::new(),
+        }
+    }
+
+    pub fn step(mut self, step: impl SagaStep + 'static) -> Self {
+        self.steps.push(Arc::new(step));
+        self
+    }
+
+    pub fn steps(&self) -> &[Arc<dyn SagaStep>] {
+        &self.steps
+    }
+
+    pub fn step_names(&self) -> Vec<String> {
+        self.steps.iter().map(|s| s.name().to_string()).collect()
+    }
+}
+
+/// Persisted saga state
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SagaState {
+    pub id: Uuid,
+    pub saga_type: String,
+    pub tenant_id: Option<Uuid>,
+    pub status: SagaStatus,
+    /// Index of the next step to execute
+    pub current_step: usize,
+    /// Names of steps that completed and have not been compensated
+    pub completed_steps: Vec<String>,
+    pub context: SagaContext,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl SagaState {
+    fn new(saga_type: &str, context: SagaContext) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::now_v7(),
+            saga_type: saga_type.to_string(),
+            tenant_id: None,
+            status: SagaStatus::Running,
+            current_step: 0,
+            completed_steps: Vec::new(),
+            context,
+            error: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+}
+
+/// Persistence for saga state
+#[async_trait]
+pub trait SagaStore: Send + Sync {
+    async fn save(&self, state: &SagaState) -> Result<()>;
+    async fn get(&self, id: Uuid) -> Result<Option<SagaState>>;
+    async fn list(&self, status: Option<SagaStatus>, limit: u32) -> Result<Vec<SagaState>>;
+    /// Sagas that were interrupted while running or compensating
+    async fn list_active(&self) -> Result<Vec<SagaState>>;
+}
+
+/// In-memory saga store for tests and single-process deployments
+#[derive(Default)]
+pub struct InMemorySagaStore {
+    sagas: RwLock<HashMap<Uuid, SagaState>>,
+}
+
+impl InMemorySagaStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl SagaStore for InMemorySagaStore {
+    async fn save(&self, state: &SagaState) -> Result<()> {
+        self.sagas.write().insert(state.id, state.clone());
+        Ok(())
+    }
+
+    async fn get(&self, id: Uuid) -> Result<Option<SagaState>> {
+        Ok(self.sagas.read().get(&id).cloned())
+    }
+
+    async fn list(&self, status: Option<SagaStatus>, limit: u32) -> Result<Vec<SagaState>> {
+        let mut sagas: Vec<SagaState> = self
+            .sagas
+            .read()
+            .values()
+            .filter(|s| status.iter().all(|st| s.status == *st))
+            .cloned()
+            .collect();
+        sagas.sort_by_key(|s| std::cmp::Reverse(s.created_at));
+        sagas.truncate(limit as usize);
+        Ok(sagas)
+    }
+
+    async fn list_active(&self) -> Result<Vec<SagaState>> {
+        Ok(self
+            .sagas
+            .read()
+            .values()
+            .filter(|s| s.status.is_active())
+            .cloned()
+            .collect())
+    }
+}
+
+/// PostgreSQL-backed saga store (`sagas` table)
+pub struct PgSagaStore {
+    pool: PgPool,
+}
+
+impl PgSagaStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl SagaStore for PgSagaStore {
+    async fn save(&self, state: &SagaState) -> Result<()> {
+        let completed = serde_json::to_value(&state.completed_steps)
+            .map_err(|e| Error::serialization(e.to_string()))?;
+        let context = serde_json::to_value(&state.context)
+            .map_err(|e| Error::serialization(e.to_string()))?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO sagas (id, saga_type, tenant_id, status, current_step, completed_steps, context, error, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            ON CONFLICT (id) DO UPDATE SET
+                status = EXCLUDED.status,
+                current_step = EXCLUDED.current_step,
+                completed_steps = EXCLUDED.completed_steps,
+                context = EXCLUDED.context,
+                error = EXCLUDED.error,
+                updated_at = EXCLUDED.updated_at
+            "#,
+        )
+        .bind(state.id)
+        .bind(&state.saga_type)
+        .bind(state.tenant_id)
+        .bind(state.status.as_str())
+        .bind(state.current_step as i32)
+        .bind(completed)
+        .bind(context)
+        .bind(&state.error)
+        .bind(state.created_at)
+        .bind(state.updated_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to save saga", e))?;
+
+        Ok(())
+    }
+
+    async fn get(&self, id: Uuid) -> Result<Option<SagaState>> {
+        let row: Option<SagaRow> = sqlx::query_as("SELECT * FROM sagas WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to get saga", e))?;
+
+        row.map(SagaState::try_from).transpose()
+    }
+
+    async fn list(&self, status: Option<SagaStatus>, limit: u32) -> Result<Vec<SagaState>> {
+        let rows: Vec<SagaRow> = sqlx::query_as(
+            r#"
+            SELECT * FROM sagas
+            WHERE ($1::TEXT IS NULL OR status = $1)
+            ORDER BY created_at DESC
+            LIMIT $2
+            "#,
+        )
+        .bind(status.map(|s| s.as_str()))
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to list sagas", e))?;
+
+        rows.into_iter().map(SagaState::try_from).collect()
+    }
+
+    async fn list_active(&self) -> Result<Vec<SagaState>> {
+        let rows: Vec<SagaRow> = sqlx::query_as(
+            "SELECT * FROM sagas WHERE status IN ('running', 'compensating') ORDER BY created_at",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to list active sagas", e))?;
+
+        // A corrupt row cannot be resumed safely; leave it in place for an
+        // operator instead of blocking every other saga
+        Ok(rows
+            .into_iter()
+            .filter_map(|row| {
+                let id = row.id;
+                SagaState::try_from(row)
+                    .map_err(
+                        |e| tracing::error!(saga_id = %id, error = %e, "Skipping unreadable saga"),
+                    )
+                    .ok()
+            })
+            .collect())
+    }
+}
+
+/// Database row for sagas
+#[derive(sqlx::FromRow)]
+struct SagaRow {
+    id: Uuid,
+    saga_type: String,
+    tenant_id: Option<Uuid>,
+    status: String,
+    current_step: i32,
+    completed_steps: serde_json::Value,
+    context: serde_json::Value,
+    error: Option<String>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl TryFrom<SagaRow> for SagaState {
+    type Error = Error;
+
+    fn try_from(row: SagaRow) -> Result<Self> {
+        Ok(SagaState {
+            id: row.id,
+            saga_type: row.saga_type,
+            tenant_id: row.tenant_id,
+            status: row.status.parse()?,
+            current_step: row.current_step.max(0) as usize,
+            completed_steps: serde_json::from_value(row.completed_steps).map_err(|e| {
+                Error::deserialization_with_source(
+                    format!("Corrupt completed_steps for saga {}", row.id),
+                    e,
+                )
+            })?,
+            context: serde_json::from_value(row.context).map_err(|e| {
+                Error::deserialization_with_source(
+                    format!("Corrupt context for saga {}", row.id),
+                    e,
+                )
+            })?,
+            error: row.error,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        })
+    }
+}
+
+/// Summary of a saga for the inspection API
+#[derive(Debug, Clone, Serialize)]
+pub struct SagaSummary {
+    pub state: SagaState,
+    /// All step names in definition order
+    pub steps: Vec<String>,
+}
+
+/// Runs saga definitions and persists their progress
+pub struct SagaCoordinator {
+    definitions: DashMap<String, Arc<SagaDefinition>>,
+    store: Arc<dyn SagaStore>,
+}
+
+impl SagaCoordinator {
+    pub fn new(store: Arc<dyn SagaStore>) -> Self {
+        Self {
+            definitions: DashMap::new(),
+            store,
+        }
+    }
+
+    /// Register a saga definition
+    pub fn register(&self, definition: SagaDefinition) {
+        self.definitions
+            .insert(definition.saga_type.clone(), Arc::new(definition));
+    }
+
+    /// Start and run a new saga to completion (or compensation)
+    pub async fn start(&self, saga_type: &str, context: SagaContext) -> Result<SagaState> {
+        self.start_for_tenant(saga_type, context, None).await
+    }
+
+    /// Start a saga scoped to a tenant
+    pub async fn start_for_tenant(
+        &self,
+        saga_type: &str,
+        context: SagaContext,
+        tenant_id: Option<Uuid>,
+    ) -> Result<SagaState> {
+        let definition = self.definition(saga_type)?;
+        let mut state = SagaState::new(saga_type, context);
+        state.tenant_id = tenant_id;
+        self.store.save(&state).await?;
+
+        self.drive(&definition, state).await
+    }
+
+    /// Resume all sagas interrupted by a crash or restart.
+    ///
+    /// A saga that fails to resume is logged and left active; the others
+    /// are still resumed.
+    pub async fn resume_incomplete(&self) -> Result<Vec<SagaState>> {
+        let mut resumed = Vec::new();
+        for state in self.store.list_active().await? {
+            let definition = match self.definition(&state.saga_type) {
+                Ok(d) => d,
+                Err(e) => {
+                    tracing::warn!(
+                        saga_id = %state.id,
+                        saga_type = %state.saga_type,
+                        "Cannot resume saga without a registered definition"
+                    );
+                    tracing::debug!(error = %e);
+                    continue;
+                }
+            };
+            tracing::info!(saga_id = %state.id, status = state.status.as_str(), "Resuming saga");
+            let id = state.id;
+            match self.drive(&definition, state).await {
+                Ok(state) => resumed.push(state),
+                Err(e) => {
+                    tracing::error!(saga_id = %id, error = %e, "Failed to resume saga");
+                }
+            }
+        }
+        Ok(resumed)
+    }
+
+    /// Inspect a single saga
+    pub async fn inspect(&self, id: Uuid) -> Result<SagaSummary> {
+        let state = self
+            .store
+            .get(id)
+            .await?
+            .ok_or_else(|| Error::not_found("saga", id.to_string()))?;
+        Ok(self.summary(state))
+    }
+
+    /// List sagas, optionally filtered by status
+    pub async fn list(&self, status: Option<SagaStatus>, limit: u32) -> Result<Vec<SagaState>> {
+        self.store.list(status, limit).await
+    }
+
+    /// List sagas with the steps of their definitions
+    pub async fn list_summaries(
+        &self,
+        status: Option<SagaStatus>,
+        limit: u32,
+    ) -> Result<Vec<SagaSummary>> {
+        Ok(self
+            .list(status, limit)
+            .await?
+            .into_iter()
+            .map(|state| self.summary(state))
+            .collect())
+    }
+
+    /// A saga with its step names; none when its type is not registered
+    fn summary(&self, state: SagaState) -> SagaSummary {
+        let steps = self
+            .definitions
+            .get(&state.saga_type)
+            .map(|d| d.step_names())
+            .unwrap_or_default();
+        SagaSummary { state, steps }
+    }
+
+    /// Registered saga types
+    pub fn saga_types(&self) -> Vec<String> {
+        self.definitions.iter().map(|d| d.key().clone()).collect()
+    }
+
+    fn definition(&self, saga_type: &str) -> Result<Arc<SagaDefinition>> {
+        self.definitions
+            .get(saga_type)
+            .map(|d| d.value().clone())
+            .ok_or_else(|| Error::not_found("saga definition", saga_type))
+    }
+
+    async fn persist(&self, state: &mut SagaState) -> Result<()> {
+        state.updated_at = Utc::now();
+        self.store.save(state).await
+    }
+
+    async fn drive(&self, definition: &SagaDefinition, mut state: SagaState) -> Result<SagaState> {
+        if state.status == SagaStatus::Running {
+            while state.current_step < definition.steps.len() {
+                let step = &definition.steps[state.current_step];
+                match step.execute(&mut state.context).await {
+                    Ok(()) => {
+                        state.completed_steps.push(step.name().to_string());
+                        state.current_step += 1;
+                        self.persist(&mut state).await?;
+                    }
+                    Err(e) => {
+                        tracing::warn!(
+                            saga_id = %state.id,
+                            step = step.name(),
+                            error = %e,
+                            "Saga step failed, compensating"
+                        );
+                        state.status = SagaStatus::Compensating;
+                        state.error = Some(format!("{}: {}", step.name(), e));
+                        self.persist(&mut state).await?;
+                        break;
+                    }
+                }
+            }
+
+            if state.status == SagaStatus::Running {
+                state.status = SagaStatus::Completed;
+                self.persist(&mut state).await?;
+                return Ok(state);
+            }
+        }
+
+        if state.status == SagaStatus::Compensating {
+            self.compensate(definition, &mut state).await?;
+        }
+
+        Ok(state)
+    }
+
+    async fn compensate(&self, definition: &SagaDefinition, state: &mut SagaState) -> Result<()> {
+        while let Some(name) = state.completed_steps.last().cloned() {
+            let Some(step) = definition.steps.iter().find(|s| s.name() == name) else {
+                state.status = SagaStatus::Failed;
+                state.error = Some(format!("Unknown step '{}' during compensation", name));
+                return self.persist(state).await;
+            };
+
+            if let Err(e) = step.compensate(&state.context).await {
+                tracing::error!(
+                    saga_id = %state.id,
+                    step = %name,
+                    error = %e,
+                    "Saga compensation failed"
+                );
+                state.status = SagaStatus::Failed;
+                state.error = Some(format!("compensation of {} failed: {}", name, e));
+                return self.persist(state).await;
+            }
+
+            state.completed_steps.pop();
+            self.persist(state).await?;
+        }
+
+        state.status = SagaStatus::Compensated;
+        self.persist(state).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    struct RecordingStep {
+        name: &'static str,
+        fail: Arc<AtomicBool>,
+        log: Arc<RwLock<Vec<String>>>,
+    }
+
+    impl RecordingStep {
+        fn new(name: &'static str, log: &Arc<RwLock<Vec<String>>>) -> Self {
+            Self {
+                name,
+                fail: Arc::new(AtomicBool::new(false)),
+                log: log.clone(),
+            }
+        }
+
+        fn failing(self) -> Self {
+            self.fail.store(true, Ordering::SeqCst);
+            self
+        }
+    }
+
+    #[async_trait]
+    impl SagaStep for RecordingStep {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        async fn execute(&self, ctx: &mut SagaContext) -> Result<()> {
+            if self.fail.load(Ordering::SeqCst) {
+                return Err(Error::internal("step failed"));
+            }
+            self.log.write().push(format!("do:{}", self.name));
+            ctx.set(self.name, serde_json::json!(true));
+            Ok(())
+        }
+
+        async fn compensate(&self, _ctx: &SagaContext) -> Result<()> {
+            self.log.write().push(format!("undo:{}", self.name));
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_saga_completes() {
+        let log = Arc::new(RwLock::new(Vec::new()));
+        let coordinator = SagaCoordinator::new(Arc::new(InMemorySagaStore::new()));
+        coordinator.register(
+            SagaDefinition::new("publish_post")
+                .step(RecordingStep::new("create_post", &log))
+                .step(RecordingStep::new("upload_media", &log)),
+        );
+
+        let state = coordinator
+            .start("publish_post", SagaContext::new())
+            .await
+            .unwrap();
+
+        assert_eq!(state.status, SagaStatus::Completed);
+        assert_eq!(state.completed_steps, vec!["create_post", "upload_media"]);
+        assert_eq!(
+            state.context.get("upload_media"),
+            Some(&serde_json::json!(true))
+        );
+
+        let summaries = coordinator.list_summaries(None, 10).await.unwrap();
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summaries[0].state.id, state.id);
+        assert_eq!(summaries[0].steps, vec!["create_post", "upload_media"]);
+    }
+
+    #[tokio::test]
+    async fn test_saga_compensates_in_reverse() {
+        let log = Arc::new(RwLock::new(Vec::new()));
+        let coordinator = SagaCoordinator::new(Arc::new(InMemorySagaStore::new()));
+        coordinator.register(
+            SagaDefinition::new("publish_post")
+                .step(RecordingStep::new("create_post", &log))
+                .step(RecordingStep::new("upload_media", &log))
+                .step(RecordingStep::new("notify", &log).failing()),
+        );
+
+        let state = coordinator
+            .start("publish_post", SagaContext::new())
+            .await
+            .unwrap();
+
+        assert_eq!(state.status, SagaStatus::Compensated);
+        assert!(state.error.as_deref().unwrap().starts_with("notify"));
+        assert_eq!(
+            *log.read(),
+            vec![
+                "do:create_post",
+                "do:upload_media",
+                "undo:upload_media",
+                "undo:create_post"
+            ]
+        );
+
+        let summary = coordinator.inspect(state.id).await.unwrap();
+        assert_eq!(summary.steps.len(), 3);
+        assert!(summary.state.completed_steps.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_resume_after_crash() {
+        let log = Arc::new(RwLock::new(Vec::new()));
+        let store = Arc::new(InMemorySagaStore::new());
+
+        // Simulate a saga persisted mid-flight by a crashed process
+        let mut state = SagaState::new("import", SagaContext::new());
+        state.current_step = 1;
+        state.completed_steps = vec!["fetch".to_string()];
+        store.save(&state).await.unwrap();
+
+        let coordinator = SagaCoordinator::new(store.clone());
+        coordinator.register(
+            SagaDefinition::new("import")
+                .step(RecordingStep::new("fetch", &log))
+                .step(RecordingStep::new("index", &log)),
+        );
+
+        let resumed = coordinator.resume_incomplete().await.unwrap();
+        assert_eq!(resumed.len(), 1);
+        assert_eq!(resumed[0].status, SagaStatus::Completed);
+        assert_eq!(*log.read(), vec!["do:index"]);
+        assert!(store.list_active().await.unwrap().is_empty());
+    }
+
+    /// Store whose writes fail for one saga
+    struct FailingStore {
+        inner: InMemorySagaStore,
+        broken: Uuid,
+    }
+
+    #[async_trait]
+    impl SagaStore for FailingStore {
+        async fn save(&self, state: &SagaState) -> Result<()> {
+            if state.id == self.broken && state.current_step > 0 {
+                return Err(Error::internal("disk full"));
+            }
+            self.inner.save(state).await
+        }
+
+        async fn get(&self, id: Uuid) -> Result<Option<SagaState>> {
+            self.inner.get(id).await
+        }
+
+        async fn list(&self, status: Option<SagaStatus>, limit: u32) -> Result<Vec<SagaState>> {
+            self.inner.list(status, limit).await
+        }
+
+        async fn list_active(&self) -> Result<Vec<SagaState>> {
+            self.inner.list_active().await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resume_continues_past_failing_saga() {
+        let log = Arc::new(RwLock::new(Vec::new()));
+        let broken = SagaState::new("import", SagaContext::new());
+        let healthy = SagaState::new("import", SagaContext::new());
+        let store = Arc::new(FailingStore {
+            inner: InMemorySagaStore::new(),
+            broken: broken.id,
+        });
+        store.save(&broken).await.unwrap();
+        store.save(&healthy).await.unwrap();
+
+        let coordinator = SagaCoordinator::new(store.clone());
+        coordinator.register(SagaDefinition::new("import").step(RecordingStep::new("fetch", &log)));
+
+        let resumed = coordinator.resume_incomplete().await.unwrap();
+        assert_eq!(resumed.len(), 1);
+        assert_eq!(resumed[0].id, healthy.id);
+        assert_eq!(resumed[0].status, SagaStatus::Completed);
+    }
+
+    #[test]
+    fn test_corrupt_row_is_an_error() {
+        let state = SagaState::new("import", SagaContext::new());
+        let row = SagaRow {
+            id: state.id,
+            saga_type: state.saga_type,
+            tenant_id: None,
+            status: "running".to_string(),
+            current_step: 1,
+            completed_steps: serde_json::json!({"not": "a list"}),
+            context: serde_json::json!({}),
+            error: None,
+            created_at: state.created_at,
+            updated_at: state.updated_at,
+        };
+        assert!(SagaState::try_from(row).is_err());
+    }
+}
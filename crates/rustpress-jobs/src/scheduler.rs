@@ -1,14 +1,71 @@
 //! Job scheduler for recurring and scheduled tasks.
+//!
+//! Tasks registered in code live for the life of the process. Schedules
+//! defined by site owners are kept in a [`ScheduleStore`] together with
+//! when they last ran, so runs missed while the server was down are caught
+//! up according to each schedule's [`CatchUp`] policy.
 
+pub use crate::cron::CronSchedule;
 use crate::job::{Job, JobPayload};
 use crate::queue::{JobQueue, Queue};
+use async_trait::async_trait;
 use chrono::{DateTime, Datelike, Duration, Utc};
 use parking_lot::RwLock;
 use rustpress_core::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
 use std::collections::HashMap;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
+/// Missed runs examined per task when catching up
+const MAX_CATCH_UP_SCAN: usize = 10_000;
+
+/// What to do about runs missed while the scheduler was not running
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "policy", rename_all = "snake_case")]
+pub enum CatchUp {
+    /// Drop missed runs; only a run within the misfire grace period happens
+    Skip,
+    /// Run once for any number of missed runs
+    #[default]
+    RunOnce,
+    /// Run once per missed run, at most `max` times
+    RunAll { max: u32 },
+}
+
+impl CatchUp {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Skip => "skip",
+            Self::RunOnce => "run_once",
+            Self::RunAll { .. } => "run_all",
+        }
+    }
+
+    /// Rebuild a policy from its stored name and limit
+    pub fn from_parts(name: &str, max: Option<u32>) -> Result<Self> {
+        match name {
+            "skip" => Ok(Self::Skip),
+            "run_once" => Ok(Self::RunOnce),
+            "run_all" => Ok(Self::RunAll {
+                max: max.unwrap_or(1),
+            }),
+            _ => Err(Error::invalid_input(
+                "catch_up",
+                format!("Unknown catch-up policy '{}'", name),
+            )),
+        }
+    }
+
+    fn max(&self) -> Option<u32> {
+        match self {
+            Self::RunAll { max } => Some(*max),
+            _ => None,
+        }
+    }
+}
+
 /// Scheduled task definition
 pub struct ScheduledTask {
     pub name: String,
@@ -17,6 +74,9 @@ pub struct ScheduledTask {
     pub enabled: bool,
     pub last_run: Option<DateTime<Utc>>,
     pub next_run: Option<DateTime<Utc>>,
+    pub catch_up: CatchUp,
+    /// Runs are recorded in the scheduler's store
+    persisted: bool,
 }
 
 impl ScheduledTask {
@@ -32,6 +92,8 @@ impl ScheduledTask {
             enabled: true,
             last_run: None,
             next_run: Some(schedule_clone.next_run_time()),
+            catch_up: CatchUp::default(),
+            persisted: false,
         }
     }
 
@@ -40,6 +102,11 @@ impl ScheduledTask {
         self
     }
 
+    pub fn with_catch_up(mut self, catch_up: CatchUp) -> Self {
+        self.catch_up = catch_up;
+        self
+    }
+
     pub fn is_due(&self) -> bool {
         if !self.enabled {
             return false;
@@ -51,6 +118,47 @@ impl ScheduledTask {
         self.last_run = Some(Utc::now());
         self.next_run = Some(self.schedule.next_run_time());
     }
+
+    /// Advance a due task past `now`; returns how many runs to dispatch
+    ///
+    /// Interval schedules simply run again. Calendar schedules count the
+    /// runs missed since the task was due and apply the catch-up policy;
+    /// a run is missed once it is more than `grace` late.
+    fn take_due(&mut self, now: DateTime<Utc>, grace: Duration) -> usize {
+        let Some(due) = self.next_run else {
+            return 0;
+        };
+
+        if self.schedule.is_interval() {
+            self.last_run = Some(now);
+            self.next_run = Some(self.schedule.next_after(now));
+            return 1;
+        }
+
+        let mut runs = 1;
+        let mut latest = due;
+        let mut next = self.schedule.next_after(due);
+        while next <= now && runs < MAX_CATCH_UP_SCAN {
+            runs += 1;
+            latest = next;
+            next = self.schedule.next_after(next);
+        }
+        if next <= now {
+            next = self.schedule.next_after(now);
+        }
+        self.next_run = Some(next);
+
+        let on_time = now - latest <= grace;
+        let runs = match self.catch_up {
+            CatchUp::Skip => usize::from(on_time),
+            CatchUp::RunOnce => 1,
+            CatchUp::RunAll { max } => runs.min(max.max(1) as usize),
+        };
+        if runs > 0 {
+            self.last_run = Some(now);
+        }
+        runs
+    }
 }
 
 /// Schedule definition
@@ -111,108 +219,69 @@ impl Schedule {
         Self::WeeklyAt(0, 0)
     }
 
+    /// Run on a cron expression, e.g. `0 3 * * MON`, in UTC
+    pub fn cron(expression: &str) -> Result<Self> {
+        Ok(Self::Cron(CronSchedule::parse(expression)?))
+    }
+
+    /// Interval schedules count from the previous run rather than the clock
+    pub fn is_interval(&self) -> bool {
+        matches!(
+            self,
+            Schedule::EverySeconds(_) | Schedule::EveryMinutes(_) | Schedule::EveryHours(_)
+        )
+    }
+
     pub fn next_run_time(&self) -> DateTime<Utc> {
-        let now = Utc::now();
+        self.next_after(Utc::now())
+    }
+
+    /// Next run strictly after `after` (or at it, for a zero interval)
+    pub fn next_after(&self, after: DateTime<Utc>) -> DateTime<Utc> {
         match self {
-            Schedule::EverySeconds(secs) => now + Duration::seconds(*secs as i64),
-            Schedule::EveryMinutes(mins) => now + Duration::minutes(*mins as i64),
-            Schedule::EveryHours(hours) => now + Duration::hours(*hours as i64),
+            Schedule::EverySeconds(secs) => after + Duration::seconds(*secs as i64),
+            Schedule::EveryMinutes(mins) => after + Duration::minutes(*mins as i64),
+            Schedule::EveryHours(hours) => after + Duration::hours(*hours as i64),
             Schedule::DailyAt(hour) => {
-                let today = now.date_naive();
                 let time =
                     chrono::NaiveTime::from_hms_opt(*hour, 0, 0).unwrap_or(chrono::NaiveTime::MIN);
-                let datetime = today.and_time(time);
-                let scheduled = datetime.and_utc();
-                if scheduled <= now {
+                let scheduled = after.date_naive().and_time(time).and_utc();
+                if scheduled <= after {
                     scheduled + Duration::days(1)
                 } else {
                     scheduled
                 }
             }
             Schedule::WeeklyAt(day, hour) => {
-                let today = now.date_naive();
+                let today = after.date_naive();
                 let time =
                     chrono::NaiveTime::from_hms_opt(*hour, 0, 0).unwrap_or(chrono::NaiveTime::MIN);
                 let current_weekday = today.weekday().num_days_from_sunday();
-                let target_day = *day;
-                let days_until = if target_day >= current_weekday {
-                    target_day - current_weekday
+                let target_day = *day % 7;
+                let days_until = (7 + target_day - current_weekday) % 7;
+                let scheduled = (today + Duration::days(days_until as i64))
+                    .and_time(time)
+                    .and_utc();
+                if scheduled <= after {
+                    scheduled + Duration::days(7)
                 } else {
-                    7 - (current_weekday - target_day)
-                };
-                let target_date = today + Duration::days(days_until as i64);
-                target_date.and_time(time).and_utc()
+                    scheduled
+                }
             }
-            Schedule::Cron(cron) => cron.next_run_time(),
+            Schedule::Cron(cron) => cron.next_after(after).unwrap_or(DateTime::<Utc>::MAX_UTC),
         }
     }
 }
 
-/// Simple cron-like schedule
-#[derive(Debug, Clone)]
-pub struct CronSchedule {
-    pub minute: Option<u32>,
-    pub hour: Option<u32>,
-    pub day_of_month: Option<u32>,
-    pub month: Option<u32>,
-    pub day_of_week: Option<u32>,
-}
-
-impl CronSchedule {
-    pub fn new() -> Self {
-        Self {
-            minute: None,
-            hour: None,
-            day_of_month: None,
-            month: None,
-            day_of_week: None,
-        }
-    }
-
-    pub fn minute(mut self, min: u32) -> Self {
-        self.minute = Some(min);
-        self
-    }
-
-    pub fn hour(mut self, hour: u32) -> Self {
-        self.hour = Some(hour);
-        self
-    }
-
-    pub fn day_of_month(mut self, day: u32) -> Self {
-        self.day_of_month = Some(day);
-        self
-    }
-
-    pub fn month(mut self, month: u32) -> Self {
-        self.month = Some(month);
-        self
-    }
-
-    pub fn day_of_week(mut self, day: u32) -> Self {
-        self.day_of_week = Some(day);
-        self
-    }
-
-    pub fn next_run_time(&self) -> DateTime<Utc> {
-        // Simplified implementation - just adds 1 day for now
-        // A real implementation would properly calculate based on cron fields
-        Utc::now() + Duration::days(1)
-    }
-}
-
-impl Default for CronSchedule {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
 /// Job scheduler
 pub struct Scheduler {
     queue: Arc<JobQueue>,
     tasks: RwLock<HashMap<String, ScheduledTask>>,
     running: Arc<AtomicBool>,
     check_interval: std::time::Duration,
+    /// How late a run may start before it counts as missed
+    misfire_grace: Duration,
+    store: Option<Arc<dyn ScheduleStore>>,
 }
 
 impl Scheduler {
@@ -222,6 +291,8 @@ impl Scheduler {
             tasks: RwLock::new(HashMap::new()),
             running: Arc::new(AtomicBool::new(false)),
             check_interval: std::time::Duration::from_secs(60),
+            misfire_grace: Duration::minutes(5),
+            store: None,
         }
     }
 
@@ -230,6 +301,67 @@ impl Scheduler {
         self
     }
 
+    pub fn with_misfire_grace(mut self, grace: std::time::Duration) -> Self {
+        self.misfire_grace = Duration::from_std(grace).unwrap_or(self.misfire_grace);
+        self
+    }
+
+    /// Keep site owners' schedules and their run times in `store`
+    pub fn with_store(mut self, store: Arc<dyn ScheduleStore>) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    /// Register every schedule saved in the store; returns how many were
+    /// loaded. Schedules that no longer parse are logged and skipped.
+    pub async fn load_stored(&self) -> Result<usize> {
+        let Some(store) = &self.store else {
+            return Ok(0);
+        };
+
+        let mut loaded = 0;
+        for definition in store.list().await? {
+            let name = definition.name.clone();
+            match definition.into_task() {
+                Ok(task) => {
+                    self.schedule(task);
+                    loaded += 1;
+                }
+                Err(e) => {
+                    tracing::error!(task = %name, error = %e, "Skipping invalid stored schedule")
+                }
+            }
+        }
+        Ok(loaded)
+    }
+
+    /// Save a schedule to the store and register it; replaces any schedule
+    /// with the same name
+    pub async fn add_stored(&self, mut definition: ScheduleDefinition) -> Result<()> {
+        let store = self
+            .store
+            .as_ref()
+            .ok_or_else(|| Error::internal("No schedule store configured"))?;
+
+        let schedule = definition.schedule()?;
+        definition.next_run = Some(schedule.next_run_time());
+        store.save(&definition).await?;
+        self.schedule(definition.into_task()?);
+        Ok(())
+    }
+
+    /// Remove a schedule from the store and the scheduler
+    pub async fn remove_stored(&self, name: &str) -> Result<bool> {
+        let store = self
+            .store
+            .as_ref()
+            .ok_or_else(|| Error::internal("No schedule store configured"))?;
+
+        let removed = store.delete(name).await?;
+        self.unschedule(name);
+        Ok(removed)
+    }
+
     /// Schedule a task
     pub fn schedule(&self, task: ScheduledTask) -> &Self {
         let name = task.name.clone();
@@ -328,44 +460,26 @@ impl Scheduler {
 
     /// Process all due tasks
     async fn process_due_tasks(&self) -> Result<()> {
-        // Collect jobs to dispatch while holding the lock, then release it before async operations
-        let jobs_to_dispatch: Vec<(String, Job)> = {
-            let mut tasks = self.tasks.write();
-            let due_task_names: Vec<String> = tasks
-                .iter()
-                .filter(|(_, t)| t.is_due())
-                .map(|(name, _)| name.clone())
-                .collect();
-
-            let mut jobs = Vec::new();
-            for task_name in due_task_names {
-                if let Some(task) = tasks.get_mut(&task_name) {
-                    let job = (task.job_factory)();
-                    task.update_schedule();
-                    jobs.push((task_name, job));
-                }
-            }
-            jobs
-        }; // Lock is released here
-
-        // Now dispatch jobs without holding the lock
-        for (task_name, job) in jobs_to_dispatch {
-            match self.queue.push(job).await {
-                Ok(job_id) => {
-                    tracing::info!(
-                        task = %task_name,
-                        job_id = %job_id,
-                        "Scheduled task dispatched"
-                    );
-                }
-                Err(e) => {
-                    tracing::error!(
-                        task = %task_name,
-                        error = %e,
-                        "Failed to dispatch scheduled task"
-                    );
+        for mut due in self.take_due_tasks() {
+            for job in std::mem::take(&mut due.jobs) {
+                match self.queue.push(job).await {
+                    Ok(job_id) => {
+                        tracing::info!(
+                            task = %due.task,
+                            job_id = %job_id,
+                            "Scheduled task dispatched"
+                        );
+                    }
+                    Err(e) => {
+                        tracing::error!(
+                            task = %due.task,
+                            error = %e,
+                            "Failed to dispatch scheduled task"
+                        );
+                    }
                 }
             }
+            self.record_run(&due).await;
         }
 
         Ok(())
@@ -373,34 +487,66 @@ impl Scheduler {
 
     /// Run due tasks once (for testing)
     pub async fn tick(&self) -> Result<u32> {
-        // Collect jobs to dispatch while holding the lock
-        let jobs_to_dispatch: Vec<Job> = {
-            let mut tasks = self.tasks.write();
-            let due_task_names: Vec<String> = tasks
-                .iter()
-                .filter(|(_, t)| t.is_due())
-                .map(|(name, _)| name.clone())
-                .collect();
-
-            let mut jobs = Vec::new();
-            for task_name in due_task_names {
-                if let Some(task) = tasks.get_mut(&task_name) {
-                    let job = (task.job_factory)();
-                    task.update_schedule();
-                    jobs.push(job);
-                }
+        let mut count = 0;
+        for mut due in self.take_due_tasks() {
+            for job in std::mem::take(&mut due.jobs) {
+                self.queue.push(job).await?;
+                count += 1;
             }
-            jobs
-        }; // Lock is released here
-
-        // Dispatch jobs without holding the lock
-        let count = jobs_to_dispatch.len() as u32;
-        for job in jobs_to_dispatch {
-            self.queue.push(job).await?;
+            self.record_run(&due).await;
         }
 
         Ok(count)
     }
+
+    /// Build the jobs of every due task and advance the tasks
+    fn take_due_tasks(&self) -> Vec<DueTask> {
+        // Collect jobs while holding the lock; it is released before any
+        // async operation
+        let now = Utc::now();
+        let mut tasks = self.tasks.write();
+        tasks
+            .values_mut()
+            .filter(|t| t.is_due())
+            .map(|task| {
+                let runs = task.take_due(now, self.misfire_grace);
+                if runs == 0 {
+                    tracing::info!(task = %task.name, "Skipping missed scheduled run");
+                } else if runs > 1 {
+                    tracing::info!(task = %task.name, runs, "Catching up missed scheduled runs");
+                }
+                DueTask {
+                    task: task.name.clone(),
+                    jobs: (0..runs).map(|_| (task.job_factory)()).collect(),
+                    persisted: task.persisted,
+                    last_run: now,
+                    next_run: task.next_run.unwrap_or(now),
+                }
+            })
+            .collect()
+    }
+
+    async fn record_run(&self, due: &DueTask) {
+        let Some(store) = self.store.as_ref().filter(|_| due.persisted) else {
+            return;
+        };
+        if let Err(e) = store
+            .record_run(&due.task, due.last_run, due.next_run)
+            .await
+        {
+            // The run happened; at worst it is caught up again after a restart
+            tracing::warn!(task = %due.task, error = %e, "Failed to record scheduled run");
+        }
+    }
+}
+
+/// Jobs to dispatch for a due task
+struct DueTask {
+    task: String,
+    jobs: Vec<Job>,
+    persisted: bool,
+    last_run: DateTime<Utc>,
+    next_run: DateTime<Utc>,
 }
 
 /// Task status info
@@ -412,6 +558,266 @@ pub struct TaskStatus {
     pub next_run: Option<DateTime<Utc>>,
 }
 
+/// Schedule defined by a site owner, as kept in a [`ScheduleStore`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduleDefinition {
+    pub name: String,
+    /// Cron expression, see [`CronSchedule::parse`]
+    pub expression: String,
+    /// IANA timezone the expression is evaluated in
+    pub timezone: String,
+    pub catch_up: CatchUp,
+    pub job_type: String,
+    pub queue: String,
+    pub payload: serde_json::Value,
+    pub enabled: bool,
+    pub last_run: Option<DateTime<Utc>>,
+    pub next_run: Option<DateTime<Utc>>,
+}
+
+impl ScheduleDefinition {
+    /// Schedule `payload` on a cron expression, in UTC
+    pub fn new<P: JobPayload>(
+        name: impl Into<String>,
+        expression: impl Into<String>,
+        payload: &P,
+    ) -> Result<Self> {
+        Ok(Self {
+            name: name.into(),
+            expression: expression.into(),
+            timezone: "UTC".to_string(),
+            catch_up: CatchUp::default(),
+            job_type: P::job_type().to_string(),
+            queue: P::queue().to_string(),
+            payload: serde_json::to_value(payload)
+                .map_err(|e| Error::serialization(e.to_string()))?,
+            enabled: true,
+            last_run: None,
+            next_run: None,
+        })
+    }
+
+    pub fn in_timezone(mut self, timezone: impl Into<String>) -> Self {
+        self.timezone = timezone.into();
+        self
+    }
+
+    pub fn with_catch_up(mut self, catch_up: CatchUp) -> Self {
+        self.catch_up = catch_up;
+        self
+    }
+
+    /// Parse the expression in the schedule's timezone
+    pub fn schedule(&self) -> Result<CronSchedule> {
+        CronSchedule::parse_in(&self.expression, &self.timezone)
+    }
+
+    fn into_task(self) -> Result<ScheduledTask> {
+        let schedule = Schedule::Cron(self.schedule()?);
+        let (job_type, queue, payload) = (self.job_type, self.queue, self.payload);
+        let mut task = ScheduledTask::new(self.name, schedule, move || {
+            Job::from_raw(job_type.clone(), queue.clone(), payload.clone())
+        })
+        .with_catch_up(self.catch_up);
+        task.enabled = self.enabled;
+        task.last_run = self.last_run;
+        // A next run in the past is caught up on the first tick
+        if self.next_run.is_some() {
+            task.next_run = self.next_run;
+        }
+        task.persisted = true;
+        Ok(task)
+    }
+}
+
+/// Persistence for site owners' schedules
+#[async_trait]
+pub trait ScheduleStore: Send + Sync {
+    async fn list(&self) -> Result<Vec<ScheduleDefinition>>;
+    async fn save(&self, definition: &ScheduleDefinition) -> Result<()>;
+    async fn delete(&self, name: &str) -> Result<bool>;
+    /// Record when a schedule last ran and is next due
+    async fn record_run(
+        &self,
+        name: &str,
+        last_run: DateTime<Utc>,
+        next_run: DateTime<Utc>,
+    ) -> Result<()>;
+}
+
+/// In-memory schedule store for tests and single-process deployments
+#[derive(Default)]
+pub struct InMemoryScheduleStore {
+    schedules: RwLock<HashMap<String, ScheduleDefinition>>,
+}
+
+impl InMemoryScheduleStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl ScheduleStore for InMemoryScheduleStore {
+    async fn list(&self) -> Result<Vec<ScheduleDefinition>> {
+        let mut schedules: Vec<ScheduleDefinition> =
+            self.schedules.read().values().cloned().collect();
+        schedules.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(schedules)
+    }
+
+    async fn save(&self, definition: &ScheduleDefinition) -> Result<()> {
+        self.schedules
+            .write()
+            .insert(definition.name.clone(), definition.clone());
+        Ok(())
+    }
+
+    async fn delete(&self, name: &str) -> Result<bool> {
+        Ok(self.schedules.write().remove(name).is_some())
+    }
+
+    async fn record_run(
+        &self,
+        name: &str,
+        last_run: DateTime<Utc>,
+        next_run: DateTime<Utc>,
+    ) -> Result<()> {
+        if let Some(definition) = self.schedules.write().get_mut(name) {
+            definition.last_run = Some(last_run);
+            definition.next_run = Some(next_run);
+        }
+        Ok(())
+    }
+}
+
+/// PostgreSQL-backed schedule store (`job_schedules` table)
+pub struct PgScheduleStore {
+    pool: PgPool,
+}
+
+impl PgScheduleStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl ScheduleStore for PgScheduleStore {
+    async fn list(&self) -> Result<Vec<ScheduleDefinition>> {
+        let rows: Vec<ScheduleRow> = sqlx::query_as("SELECT * FROM job_schedules ORDER BY name")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to list job schedules", e))?;
+
+        rows.into_iter().map(ScheduleDefinition::try_from).collect()
+    }
+
+    async fn save(&self, definition: &ScheduleDefinition) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO job_schedules (name, expression, timezone, catch_up, catch_up_max, job_type, queue, payload, enabled, last_run_at, next_run_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            ON CONFLICT (name) DO UPDATE SET
+                expression = EXCLUDED.expression,
+                timezone = EXCLUDED.timezone,
+                catch_up = EXCLUDED.catch_up,
+                catch_up_max = EXCLUDED.catch_up_max,
+                job_type = EXCLUDED.job_type,
+                queue = EXCLUDED.queue,
+                payload = EXCLUDED.payload,
+                enabled = EXCLUDED.enabled,
+                last_run_at = EXCLUDED.last_run_at,
+                next_run_at = EXCLUDED.next_run_at,
+                updated_at = NOW()
+            "#,
+        )
+        .bind(&definition.name)
+        .bind(&definition.expression)
+        .bind(&definition.timezone)
+        .bind(definition.catch_up.as_str())
+        .bind(definition.catch_up.max().map(|max| max as i32))
+        .bind(&definition.job_type)
+        .bind(&definition.queue)
+        .bind(&definition.payload)
+        .bind(definition.enabled)
+        .bind(definition.last_run)
+        .bind(definition.next_run)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to save job schedule", e))?;
+
+        Ok(())
+    }
+
+    async fn delete(&self, name: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM job_schedules WHERE name = $1")
+            .bind(name)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to delete job schedule", e))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn record_run(
+        &self,
+        name: &str,
+        last_run: DateTime<Utc>,
+        next_run: DateTime<Utc>,
+    ) -> Result<()> {
+        sqlx::query(
+            "UPDATE job_schedules SET last_run_at = $2, next_run_at = $3, updated_at = NOW() WHERE name = $1",
+        )
+        .bind(name)
+        .bind(last_run)
+        .bind(next_run)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to record scheduled run", e))?;
+
+        Ok(())
+    }
+}
+
+/// Database row for job schedules
+#[derive(sqlx::FromRow)]
+struct ScheduleRow {
+    name: String,
+    expression: String,
+    timezone: String,
+    catch_up: String,
+    catch_up_max: Option<i32>,
+    job_type: String,
+    queue: String,
+    payload: serde_json::Value,
+    enabled: bool,
+    last_run_at: Option<DateTime<Utc>>,
+    next_run_at: Option<DateTime<Utc>>,
+}
+
+impl TryFrom<ScheduleRow> for ScheduleDefinition {
+    type Error = Error;
+
+    fn try_from(row: ScheduleRow) -> Result<Self> {
+        Ok(ScheduleDefinition {
+            catch_up: CatchUp::from_parts(
+                &row.catch_up,
+                row.catch_up_max.map(|m| m.max(1) as u32),
+            )?,
+            name: row.name,
+            expression: row.expression,
+            timezone: row.timezone,
+            job_type: row.job_type,
+            queue: row.queue,
+            payload: row.payload,
+            enabled: row.enabled,
+            last_run: row.last_run_at,
+            next_run: row.next_run_at,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -461,4 +867,114 @@ mod tests {
 
         assert!(!task.is_due());
     }
+
+    fn test_queue() -> Arc<JobQueue> {
+        // Never connects; the stored-schedule tests do not dispatch jobs
+        let pool = PgPool::connect_lazy("postgres://localhost/rustpress_test").unwrap();
+        Arc::new(JobQueue::new(pool))
+    }
+
+    fn cleanup_task(schedule: Schedule) -> ScheduledTask {
+        ScheduledTask::new("cleanup", schedule, || {
+            Job::new(crate::job::jobs::CleanupJob {
+                cleanup_type: "test".to_string(),
+                older_than_days: 30,
+            })
+        })
+    }
+
+    fn missed_hourly(catch_up: CatchUp) -> (ScheduledTask, DateTime<Utc>) {
+        let now: DateTime<Utc> = "2024-06-01T12:30:00Z".parse().unwrap();
+        let mut task = cleanup_task(Schedule::cron("0 * * * *").unwrap()).with_catch_up(catch_up);
+        // Down since 08:30; runs at 09:00, 10:00, 11:00 and 12:00 were missed
+        task.next_run = Some("2024-06-01T09:00:00Z".parse().unwrap());
+        (task, now)
+    }
+
+    #[test]
+    fn test_catch_up_policies() {
+        let grace = Duration::minutes(5);
+
+        let (mut task, now) = missed_hourly(CatchUp::Skip);
+        assert_eq!(task.take_due(now, grace), 0);
+        assert_eq!(task.last_run, None);
+        assert_eq!(task.next_run, Some("2024-06-01T13:00:00Z".parse().unwrap()));
+
+        let (mut task, now) = missed_hourly(CatchUp::RunOnce);
+        assert_eq!(task.take_due(now, grace), 1);
+        assert_eq!(task.last_run, Some(now));
+
+        let (mut task, now) = missed_hourly(CatchUp::RunAll { max: 10 });
+        assert_eq!(task.take_due(now, grace), 4);
+
+        let (mut task, now) = missed_hourly(CatchUp::RunAll { max: 2 });
+        assert_eq!(task.take_due(now, grace), 2);
+        assert_eq!(task.next_run, Some("2024-06-01T13:00:00Z".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_skip_runs_within_grace() {
+        let mut task =
+            cleanup_task(Schedule::cron("0 * * * *").unwrap()).with_catch_up(CatchUp::Skip);
+        task.next_run = Some("2024-06-01T12:00:00Z".parse().unwrap());
+        let now = "2024-06-01T12:03:00Z".parse().unwrap();
+
+        assert_eq!(task.take_due(now, Duration::minutes(5)), 1);
+    }
+
+    #[test]
+    fn test_catch_up_from_parts() {
+        assert_eq!(CatchUp::from_parts("skip", None).unwrap(), CatchUp::Skip);
+        assert_eq!(
+            CatchUp::from_parts("run_all", Some(3)).unwrap(),
+            CatchUp::RunAll { max: 3 }
+        );
+        assert!(CatchUp::from_parts("sometimes", None).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_stored_schedules() {
+        let store = Arc::new(InMemoryScheduleStore::new());
+        let scheduler = Scheduler::new(test_queue()).with_store(store.clone());
+
+        let payload = crate::job::jobs::CleanupJob {
+            cleanup_type: "sessions".to_string(),
+            older_than_days: 7,
+        };
+        let definition = ScheduleDefinition::new("nightly-cleanup", "30 2 * * *", &payload)
+            .unwrap()
+            .in_timezone("Europe/Berlin");
+        scheduler.add_stored(definition).await.unwrap();
+
+        let stored = store.list().await.unwrap();
+        assert_eq!(stored.len(), 1);
+        assert!(stored[0].next_run.is_some());
+        assert_eq!(scheduler.list_tasks().len(), 1);
+
+        // A restarted scheduler picks the schedule up again
+        let restarted = Scheduler::new(test_queue()).with_store(store.clone());
+        assert_eq!(restarted.load_stored().await.unwrap(), 1);
+
+        assert!(scheduler.remove_stored("nightly-cleanup").await.unwrap());
+        assert!(store.list().await.unwrap().is_empty());
+        assert!(scheduler.list_tasks().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_invalid_stored_schedule_rejected() {
+        let scheduler =
+            Scheduler::new(test_queue()).with_store(Arc::new(InMemoryScheduleStore::new()));
+        let payload = crate::job::jobs::CleanupJob {
+            cleanup_type: "sessions".to_string(),
+            older_than_days: 7,
+        };
+
+        let bad_expression = ScheduleDefinition::new("bad", "61 * * * *", &payload).unwrap();
+        assert!(scheduler.add_stored(bad_expression).await.is_err());
+
+        let bad_timezone = ScheduleDefinition::new("bad", "0 * * * *", &payload)
+            .unwrap()
+            .in_timezone("Mars/Olympus");
+        assert!(scheduler.add_stored(bad_timezone).await.is_err());
+    }
 }
@@ -0,0 +1,619 @@
+//! Service levels per job type.
+//!
+//! An administrator sets, per job type, how long a run may take, how long a
+//! due job may wait for a worker and what share of attempts may fail. The
+//! `evaluate_job_slas` job checks the [execution audit](crate::audit) of a
+//! recent window against them and announces breaches as
+//! `job.sla_breached` events, which reach live dashboards like any other
+//! notification.
+
+use crate::audit::{ExecutionAudit, JobTypeStats};
+use crate::job::{JobHandler, JobPayload};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rustpress_core::error::{Error, Result};
+use rustpress_events::{DomainEvent, EventBus};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// Default window of history an SLA is judged on (seconds)
+pub const DEFAULT_SLA_WINDOW_SECS: u64 = 3600;
+
+/// How long job execution history is kept (days)
+pub const EXECUTION_RETENTION_DAYS: i64 = 30;
+
+/// Service levels of one job type; unset limits are not checked
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct JobSla {
+    pub job_type: String,
+    /// Longest a single attempt may run
+    pub max_duration_secs: Option<i64>,
+    /// Longest a due job may wait for a worker
+    pub max_queue_latency_secs: Option<i64>,
+    /// Highest share of failed attempts, between 0 and 1
+    pub max_failure_rate: Option<f64>,
+    /// Attempts needed in the window before the failure rate is judged
+    pub min_executions: i32,
+    pub enabled: bool,
+    /// Last time a breach was announced
+    pub alerted_at: Option<DateTime<Utc>>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl JobSla {
+    pub fn new(job_type: impl Into<String>) -> Self {
+        Self {
+            job_type: job_type.into(),
+            max_duration_secs: None,
+            max_queue_latency_secs: None,
+            max_failure_rate: None,
+            min_executions: 5,
+            enabled: true,
+            alerted_at: None,
+            updated_at: Utc::now(),
+        }
+    }
+
+    pub fn max_duration(mut self, secs: i64) -> Self {
+        self.max_duration_secs = Some(secs);
+        self
+    }
+
+    pub fn max_queue_latency(mut self, secs: i64) -> Self {
+        self.max_queue_latency_secs = Some(secs);
+        self
+    }
+
+    pub fn max_failure_rate(mut self, rate: f64) -> Self {
+        self.max_failure_rate = Some(rate);
+        self
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.job_type.trim().is_empty() {
+            return Err(Error::invalid_input("job_type", "Job type is required"));
+        }
+        if matches!(self.max_duration_secs, Some(secs) if secs <= 0) {
+            return Err(Error::invalid_input(
+                "max_duration_secs",
+                "Maximum duration must be positive",
+            ));
+        }
+        if matches!(self.max_queue_latency_secs, Some(secs) if secs <= 0) {
+            return Err(Error::invalid_input(
+                "max_queue_latency_secs",
+                "Maximum queue latency must be positive",
+            ));
+        }
+        if matches!(self.max_failure_rate, Some(rate) if !(0.0..=1.0).contains(&rate)) {
+            return Err(Error::invalid_input(
+                "max_failure_rate",
+                "Maximum failure rate must be between 0 and 1",
+            ));
+        }
+        if self.min_executions < 1 {
+            return Err(Error::invalid_input(
+                "min_executions",
+                "At least one execution is needed to judge the failure rate",
+            ));
+        }
+        if self.max_duration_secs.is_none()
+            && self.max_queue_latency_secs.is_none()
+            && self.max_failure_rate.is_none()
+        {
+            return Err(Error::validation("An SLA needs at least one limit"));
+        }
+        Ok(())
+    }
+
+    /// Limits `stats` goes over
+    pub fn evaluate(&self, stats: &JobTypeStats) -> Vec<SlaBreach> {
+        let mut breaches = Vec::new();
+        if !self.enabled || stats.executions == 0 {
+            return breaches;
+        }
+
+        if let Some(limit) = self.max_duration_secs {
+            let observed = stats.max_duration_ms as f64 / 1000.0;
+            if observed > limit as f64 {
+                breaches.push(SlaBreach::new(
+                    stats,
+                    SlaMetric::Duration,
+                    limit as f64,
+                    observed,
+                ));
+            }
+        }
+        if let Some(limit) = self.max_queue_latency_secs {
+            let observed = stats.max_queue_latency_ms as f64 / 1000.0;
+            if observed > limit as f64 {
+                breaches.push(SlaBreach::new(
+                    stats,
+                    SlaMetric::QueueLatency,
+                    limit as f64,
+                    observed,
+                ));
+            }
+        }
+        if let Some(limit) = self.max_failure_rate {
+            let observed = stats.failure_rate();
+            if stats.executions >= self.min_executions as i64 && observed > limit {
+                breaches.push(SlaBreach::new(
+                    stats,
+                    SlaMetric::FailureRate,
+                    limit,
+                    observed,
+                ));
+            }
+        }
+
+        breaches
+    }
+}
+
+/// What an SLA limits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SlaMetric {
+    /// Seconds a single attempt ran
+    Duration,
+    /// Seconds a due job waited for a worker
+    QueueLatency,
+    /// Share of attempts that failed
+    FailureRate,
+}
+
+/// A limit a job type went over
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SlaBreach {
+    pub job_type: String,
+    pub metric: SlaMetric,
+    pub limit: f64,
+    pub observed: f64,
+    /// Attempts in the window
+    pub executions: i64,
+}
+
+impl SlaBreach {
+    fn new(stats: &JobTypeStats, metric: SlaMetric, limit: f64, observed: f64) -> Self {
+        Self {
+            job_type: stats.job_type.clone(),
+            metric,
+            limit,
+            observed,
+            executions: stats.executions,
+        }
+    }
+}
+
+/// SLAs kept in the `job_slas` table
+pub struct SlaStore {
+    pool: PgPool,
+}
+
+impl SlaStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn list(&self) -> Result<Vec<JobSla>> {
+        sqlx::query_as(
+            r#"
+            SELECT job_type, max_duration_secs, max_queue_latency_secs, max_failure_rate,
+                   min_executions, enabled, alerted_at, updated_at
+            FROM job_slas
+            ORDER BY job_type
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to list job SLAs", e))
+    }
+
+    pub async fn get(&self, job_type: &str) -> Result<Option<JobSla>> {
+        sqlx::query_as(
+            r#"
+            SELECT job_type, max_duration_secs, max_queue_latency_secs, max_failure_rate,
+                   min_executions, enabled, alerted_at, updated_at
+            FROM job_slas
+            WHERE job_type = $1
+            "#,
+        )
+        .bind(job_type)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to get job SLA", e))
+    }
+
+    /// Create or replace the SLA of a job type
+    pub async fn save(&self, sla: &JobSla) -> Result<JobSla> {
+        sla.validate()?;
+
+        sqlx::query_as(
+            r#"
+            INSERT INTO job_slas (
+                job_type, max_duration_secs, max_queue_latency_secs, max_failure_rate,
+                min_executions, enabled
+            )
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (job_type) DO UPDATE SET
+                max_duration_secs = EXCLUDED.max_duration_secs,
+                max_queue_latency_secs = EXCLUDED.max_queue_latency_secs,
+                max_failure_rate = EXCLUDED.max_failure_rate,
+                min_executions = EXCLUDED.min_executions,
+                enabled = EXCLUDED.enabled,
+                updated_at = NOW()
+            RETURNING job_type, max_duration_secs, max_queue_latency_secs, max_failure_rate,
+                      min_executions, enabled, alerted_at, updated_at
+            "#,
+        )
+        .bind(&sla.job_type)
+        .bind(sla.max_duration_secs)
+        .bind(sla.max_queue_latency_secs)
+        .bind(sla.max_failure_rate)
+        .bind(sla.min_executions)
+        .bind(sla.enabled)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to save job SLA", e))
+    }
+
+    /// Remove the SLA of a job type; returns whether it existed
+    pub async fn delete(&self, job_type: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM job_slas WHERE job_type = $1")
+            .bind(job_type)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to delete job SLA", e))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Note that a breach of `job_type` was announced at `at`
+    pub async fn mark_alerted(&self, job_type: &str, at: DateTime<Utc>) -> Result<()> {
+        sqlx::query("UPDATE job_slas SET alerted_at = $2 WHERE job_type = $1")
+            .bind(job_type)
+            .bind(at)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to update job SLA", e))?;
+
+        Ok(())
+    }
+}
+
+/// How one job type did over the window
+#[derive(Debug, Clone, Serialize)]
+pub struct JobTypeHealth {
+    #[serde(flatten)]
+    pub stats: JobTypeStats,
+    pub failure_rate: f64,
+    pub sla: Option<JobSla>,
+    pub breaches: Vec<SlaBreach>,
+}
+
+/// Execution summary of every job type that ran or has an SLA
+#[derive(Debug, Clone, Serialize)]
+pub struct JobHealth {
+    pub window_secs: u64,
+    pub generated_at: DateTime<Utc>,
+    /// No SLA is breached
+    pub healthy: bool,
+    pub executions: i64,
+    pub failure_rate: f64,
+    pub job_types: Vec<JobTypeHealth>,
+}
+
+impl JobHealth {
+    /// Summary of `stats` judged against `slas`
+    pub fn build(window_secs: u64, stats: Vec<JobTypeStats>, slas: Vec<JobSla>) -> Self {
+        let mut slas: BTreeMap<String, JobSla> = slas
+            .into_iter()
+            .map(|sla| (sla.job_type.clone(), sla))
+            .collect();
+        let mut by_type: BTreeMap<String, JobTypeStats> = stats
+            .into_iter()
+            .map(|stats| (stats.job_type.clone(), stats))
+            .collect();
+        for job_type in slas.keys() {
+            by_type
+                .entry(job_type.clone())
+                .or_insert_with(|| JobTypeStats::empty(job_type.clone()));
+        }
+
+        let job_types: Vec<JobTypeHealth> = by_type
+            .into_values()
+            .map(|stats| {
+                let sla = slas.remove(&stats.job_type);
+                let breaches = sla
+                    .as_ref()
+                    .map(|sla| sla.evaluate(&stats))
+                    .unwrap_or_default();
+                JobTypeHealth {
+                    failure_rate: stats.failure_rate(),
+                    stats,
+                    sla,
+                    breaches,
+                }
+            })
+            .collect();
+
+        let executions: i64 = job_types.iter().map(|h| h.stats.executions).sum();
+        let failures: i64 = job_types
+            .iter()
+            .map(|h| h.stats.retried + h.stats.failed)
+            .sum();
+
+        Self {
+            window_secs,
+            generated_at: Utc::now(),
+            healthy: job_types.iter().all(|h| h.breaches.is_empty()),
+            executions,
+            failure_rate: if executions == 0 {
+                0.0
+            } else {
+                failures as f64 / executions as f64
+            },
+            job_types,
+        }
+    }
+
+    pub fn breaches(&self) -> impl Iterator<Item = &SlaBreach> {
+        self.job_types.iter().flat_map(|h| h.breaches.iter())
+    }
+}
+
+/// Checks the execution audit against job SLAs
+pub struct SlaMonitor {
+    audit: ExecutionAudit,
+    slas: SlaStore,
+    events: Option<Arc<EventBus>>,
+}
+
+impl SlaMonitor {
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            audit: ExecutionAudit::new(pool.clone()),
+            slas: SlaStore::new(pool),
+            events: None,
+        }
+    }
+
+    /// Publish `job.sla_breached` events on the bus
+    pub fn with_events(mut self, events: Arc<EventBus>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    pub fn audit(&self) -> &ExecutionAudit {
+        &self.audit
+    }
+
+    pub fn slas(&self) -> &SlaStore {
+        &self.slas
+    }
+
+    /// How jobs did over the last `window_secs`
+    pub async fn health(&self, window_secs: u64) -> Result<JobHealth> {
+        let since = Utc::now() - chrono::Duration::seconds(window_secs as i64);
+        let stats = self.audit.stats(since).await?;
+        let slas = self.slas.list().await?;
+        Ok(JobHealth::build(window_secs, stats, slas))
+    }
+
+    /// Check the last `window_secs` and announce breaches. A job type
+    /// alerts at most once per window.
+    pub async fn evaluate(&self, window_secs: u64) -> Result<JobHealth> {
+        let health = self.health(window_secs).await?;
+        let now = Utc::now();
+        let quiet_since = now - chrono::Duration::seconds(window_secs as i64);
+
+        for job_type in &health.job_types {
+            if job_type.breaches.is_empty() {
+                continue;
+            }
+            let recently_alerted = job_type
+                .sla
+                .as_ref()
+                .and_then(|sla| sla.alerted_at)
+                .is_some_and(|at| at > quiet_since);
+            if recently_alerted {
+                continue;
+            }
+
+            for breach in &job_type.breaches {
+                tracing::warn!(
+                    job_type = %breach.job_type,
+                    metric = ?breach.metric,
+                    limit = breach.limit,
+                    observed = breach.observed,
+                    "Job SLA breached"
+                );
+            }
+            self.alert(job_type, window_secs).await;
+            self.slas
+                .mark_alerted(&job_type.stats.job_type, now)
+                .await?;
+        }
+
+        Ok(health)
+    }
+
+    async fn alert(&self, job_type: &JobTypeHealth, window_secs: u64) {
+        let Some(events) = &self.events else {
+            return;
+        };
+
+        let event = DomainEvent::new(
+            "job.sla_breached",
+            serde_json::json!({
+                "job_type": job_type.stats.job_type,
+                "window_secs": window_secs,
+                "executions": job_type.stats.executions,
+                "breaches": job_type.breaches,
+            }),
+        );
+        if let Err(e) = events.publish(event).await {
+            tracing::warn!(job_type = %job_type.stats.job_type, error = %e, "Failed to publish SLA alert");
+        }
+    }
+}
+
+/// Periodic SLA check, which also prunes old execution history
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvaluateJobSlasJob {
+    /// Window of history to judge (seconds)
+    pub window_secs: u64,
+}
+
+impl Default for EvaluateJobSlasJob {
+    fn default() -> Self {
+        Self {
+            window_secs: DEFAULT_SLA_WINDOW_SECS,
+        }
+    }
+}
+
+impl JobPayload for EvaluateJobSlasJob {
+    fn job_type() -> &'static str {
+        "evaluate_job_slas"
+    }
+
+    fn max_attempts() -> u32 {
+        1
+    }
+
+    fn timeout_secs() -> u64 {
+        120
+    }
+}
+
+/// Handler for [`EvaluateJobSlasJob`]
+#[derive(Clone)]
+pub struct EvaluateJobSlasHandler {
+    monitor: Arc<SlaMonitor>,
+}
+
+impl EvaluateJobSlasHandler {
+    pub fn new(monitor: Arc<SlaMonitor>) -> Self {
+        Self { monitor }
+    }
+}
+
+#[async_trait]
+impl JobHandler for EvaluateJobSlasHandler {
+    type Payload = EvaluateJobSlasJob;
+
+    async fn handle(&self, payload: Self::Payload) -> Result<()> {
+        let health = self.monitor.evaluate(payload.window_secs.max(60)).await?;
+        tracing::debug!(
+            healthy = health.healthy,
+            executions = health.executions,
+            "Job SLAs evaluated"
+        );
+
+        let cutoff = Utc::now() - chrono::Duration::days(EXECUTION_RETENTION_DAYS);
+        let pruned = self.monitor.audit().prune(cutoff).await?;
+        if pruned > 0 {
+            tracing::info!(count = pruned, "Pruned job execution history");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(job_type: &str, executions: i64, failed: i64) -> JobTypeStats {
+        JobTypeStats {
+            job_type: job_type.to_string(),
+            executions,
+            completed: executions - failed,
+            failed,
+            max_duration_ms: 45_000,
+            max_queue_latency_ms: 2_000,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_sla_validation() {
+        assert!(JobSla::new("send_email").validate().is_err());
+        assert!(JobSla::new("send_email")
+            .max_duration(30)
+            .validate()
+            .is_ok());
+        assert!(JobSla::new("send_email")
+            .max_duration(0)
+            .validate()
+            .is_err());
+        assert!(JobSla::new("send_email")
+            .max_failure_rate(1.5)
+            .validate()
+            .is_err());
+        assert!(JobSla::new(" ").max_queue_latency(5).validate().is_err());
+    }
+
+    #[test]
+    fn test_sla_evaluation() {
+        let sla = JobSla::new("send_email")
+            .max_duration(30)
+            .max_queue_latency(5)
+            .max_failure_rate(0.1);
+
+        let breaches = sla.evaluate(&stats("send_email", 10, 2));
+        let metrics: Vec<_> = breaches.iter().map(|b| b.metric).collect();
+        assert_eq!(metrics, vec![SlaMetric::Duration, SlaMetric::FailureRate]);
+        assert_eq!(breaches[0].observed, 45.0);
+        assert_eq!(breaches[1].observed, 0.2);
+
+        // Too few attempts to judge the failure rate
+        let breaches = sla.evaluate(&stats("send_email", 2, 1));
+        assert_eq!(breaches.len(), 1);
+        assert_eq!(breaches[0].metric, SlaMetric::Duration);
+
+        // Nothing ran, nothing breached
+        assert!(sla.evaluate(&JobTypeStats::empty("send_email")).is_empty());
+
+        let mut disabled = sla.clone();
+        disabled.enabled = false;
+        assert!(disabled.evaluate(&stats("send_email", 10, 2)).is_empty());
+    }
+
+    #[test]
+    fn test_job_health_summary() {
+        let health = JobHealth::build(
+            3600,
+            vec![
+                stats("send_email", 10, 2),
+                stats("clean_theme_previews", 4, 0),
+            ],
+            vec![
+                JobSla::new("send_email").max_failure_rate(0.1),
+                JobSla::new("publish_scheduled_posts").max_queue_latency(60),
+            ],
+        );
+
+        assert!(!health.healthy);
+        assert_eq!(health.executions, 14);
+        let job_types: Vec<_> = health
+            .job_types
+            .iter()
+            .map(|h| h.stats.job_type.as_str())
+            .collect();
+        assert_eq!(
+            job_types,
+            vec![
+                "clean_theme_previews",
+                "publish_scheduled_posts",
+                "send_email"
+            ]
+        );
+        assert!(health.job_types[0].sla.is_none());
+        assert_eq!(health.job_types[1].stats.executions, 0);
+        assert_eq!(health.breaches().count(), 1);
+    }
+}
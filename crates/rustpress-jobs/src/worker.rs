@@ -1,11 +1,16 @@
 //! Job worker implementation.
 
+use crate::audit::{ExecutionAudit, ExecutionOutcome, JobExecution};
 use crate::job::{Job, JobHandler, JobPayload};
 use crate::queue::{JobQueue, Queue};
+use crate::retry::RetryPolicy;
 use async_trait::async_trait;
+use chrono::{DateTime, Datelike, TimeZone, Utc};
 use dashmap::DashMap;
 use parking_lot::RwLock;
 use rustpress_core::error::{Error, Result};
+use rustpress_core::tenant::{with_site, MeteredResource, UsageMeter};
+use rustpress_events::{DomainEvent, EventBus};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
@@ -19,6 +24,33 @@ pub struct Worker {
     handlers: Arc<DashMap<String, Arc<dyn JobHandlerDyn>>>,
     config: WorkerConfig,
     running: Arc<AtomicBool>,
+    events: Option<Arc<EventBus>>,
+    audit: Option<Arc<ExecutionAudit>>,
+    pause: PauseSwitch,
+    meter: Option<Arc<dyn UsageMeter>>,
+}
+
+/// Shared switch that holds back job processing while set. Queues listed
+/// in [`WorkerConfig::unpaused_queues`] keep running.
+#[derive(Debug, Clone, Default)]
+pub struct PauseSwitch(Arc<AtomicBool>);
+
+impl PauseSwitch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn pause(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn resume(&self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
 }
 
 /// Worker configuration
@@ -32,6 +64,8 @@ pub struct WorkerConfig {
     pub sleep_on_empty: Duration,
     /// Maximum jobs to process before stopping (None = unlimited)
     pub max_jobs: Option<u64>,
+    /// Queues still processed while the worker is paused
+    pub unpaused_queues: Vec<String>,
 }
 
 impl Default for WorkerConfig {
@@ -41,6 +75,7 @@ impl Default for WorkerConfig {
             concurrency: 4,
             sleep_on_empty: Duration::from_secs(1),
             max_jobs: None,
+            unpaused_queues: Vec::new(),
         }
     }
 }
@@ -52,6 +87,10 @@ impl Worker {
             handlers: Arc::new(DashMap::new()),
             config: WorkerConfig::default(),
             running: Arc::new(AtomicBool::new(false)),
+            events: None,
+            audit: None,
+            pause: PauseSwitch::new(),
+            meter: None,
         }
     }
 
@@ -61,9 +100,38 @@ impl Worker {
             handlers: Arc::new(DashMap::new()),
             config,
             running: Arc::new(AtomicBool::new(false)),
+            events: None,
+            audit: None,
+            pause: PauseSwitch::new(),
+            meter: None,
         }
     }
 
+    /// Publish `job.completed` and `job.failed` events on the bus
+    pub fn with_events(mut self, events: Arc<EventBus>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Record every finished attempt in the execution audit
+    pub fn with_audit(mut self, audit: Arc<ExecutionAudit>) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    /// Hold back jobs while `pause` is set
+    pub fn with_pause(mut self, pause: PauseSwitch) -> Self {
+        self.pause = pause;
+        self
+    }
+
+    /// Record the time jobs of a site run for against its job time quota.
+    /// Jobs of sites out of job time wait for the next month.
+    pub fn with_meter(mut self, meter: Arc<dyn UsageMeter>) -> Self {
+        self.meter = Some(meter);
+        self
+    }
+
     /// Register a job handler
     pub fn register<H, P>(&self, handler: H)
     where
@@ -102,7 +170,13 @@ impl Worker {
 
             let mut found_job = false;
 
+            let paused = self.pause.is_paused();
+
             for queue_name in &self.config.queues {
+                if paused && !self.config.unpaused_queues.contains(queue_name) {
+                    continue;
+                }
+
                 // Acquire permit before fetching job
                 let permit = semaphore.clone().acquire_owned().await.unwrap();
 
@@ -112,6 +186,9 @@ impl Worker {
 
                     let handlers = self.handlers.clone();
                     let queue = self.queue.clone();
+                    let events = self.events.clone();
+                    let audit = self.audit.clone();
+                    let meter = self.meter.clone();
 
                     // Process job in background
                     tokio::spawn(async move {
@@ -119,7 +196,16 @@ impl Worker {
                         let job_id = job.id;
                         let job_type = job.job_type.clone();
 
-                        match Self::process_job(&handlers, &queue, job).await {
+                        let outcome = Self::process_job(
+                            &handlers,
+                            &queue,
+                            events.as_deref(),
+                            audit.as_deref(),
+                            meter.as_deref(),
+                            job,
+                        )
+                        .await;
+                        match outcome {
                             Ok(()) => {
                                 tracing::debug!(job_id = %job_id, job_type = %job_type, "Job processed successfully");
                             }
@@ -158,58 +244,208 @@ impl Worker {
     async fn process_job(
         handlers: &DashMap<String, Arc<dyn JobHandlerDyn>>,
         queue: &JobQueue,
+        events: Option<&EventBus>,
+        audit: Option<&ExecutionAudit>,
+        meter: Option<&dyn UsageMeter>,
         job: Job,
     ) -> Result<()> {
         let job_id = job.id;
         let job_type = job.job_type.clone();
+        let started_at = Utc::now();
+
+        // A job reserved again after its last attempt's lease ran out has
+        // no attempts left to run
+        if job.attempts > job.max_attempts {
+            let error = "Job lease expired on its last attempt";
+            queue.bury(&job, error).await?;
+            Self::record(
+                audit,
+                &job,
+                started_at,
+                ExecutionOutcome::Failed,
+                Some(error),
+            )
+            .await;
+            Self::notify(events, &job, Some(error)).await;
+            return Ok(());
+        }
+
+        // Jobs of a site out of job time wait for the next month
+        let metered = meter.zip(job.tenant_id);
+        if let Some((meter, site)) = metered {
+            if let Err(e) = meter.check(site, MeteredResource::JobTime, 1).await {
+                tracing::info!(job_id = %job_id, site = %site, error = %e, "Job deferred to next month");
+                queue
+                    .release(&job, secs_until_next_month(started_at))
+                    .await?;
+                return Ok(());
+            }
+        }
 
         // Find handler
         let handler = handlers.get(&job_type).map(|h| h.clone());
 
         match handler {
             Some(handler) => {
-                // Process with timeout
+                // Process with timeout, renewing the lease meanwhile
                 let timeout = Duration::from_secs(job.timeout_secs);
-                let result = tokio::time::timeout(timeout, handler.handle_job(&job)).await;
+                let run = tokio::time::timeout(
+                    timeout,
+                    with_site(job.tenant_id, handler.handle_job(&job)),
+                );
+                tokio::pin!(run);
+                let mut heartbeat = tokio::time::interval(queue.heartbeat_interval());
+                heartbeat.tick().await;
+
+                let result = loop {
+                    tokio::select! {
+                        result = &mut run => break result,
+                        _ = heartbeat.tick() => match queue.heartbeat(&job).await {
+                            Ok(true) => {}
+                            Ok(false) => {
+                                // Another worker took the job over; leave it to them
+                                tracing::warn!(job_id = %job_id, job_type = %job_type, "Job lease lost, abandoning job");
+                                return Ok(());
+                            }
+                            Err(e) => {
+                                tracing::warn!(job_id = %job_id, error = %e, "Failed to renew job lease");
+                            }
+                        },
+                    }
+                };
+
+                if let Some((meter, site)) = metered {
+                    let secs = (Utc::now() - started_at).num_milliseconds().max(0) as f64 / 1000.0;
+                    meter
+                        .record(site, MeteredResource::JobTime, secs.ceil() as i64)
+                        .await;
+                }
 
-                match result {
+                let error = match result {
                     Ok(Ok(())) => {
-                        queue.complete(job_id).await?;
-                    }
-                    Ok(Err(e)) => {
-                        let error = e.to_string();
-                        if job.can_retry() {
-                            // Retry with exponential backoff
-                            let delay = 60 * (2_u64.pow(job.attempts - 1));
-                            queue.release(job_id, delay).await?;
-                        } else {
-                            queue.fail(job_id, &error).await?;
-                        }
-                    }
-                    Err(_) => {
-                        let error = "Job timed out";
-                        if job.can_retry() {
-                            queue.release(job_id, 60).await?;
-                        } else {
-                            queue.fail(job_id, error).await?;
-                        }
+                        queue.complete(&job).await?;
+                        Self::record(audit, &job, started_at, ExecutionOutcome::Completed, None)
+                            .await;
+                        Self::notify(events, &job, None).await;
+                        return Ok(());
                     }
+                    Ok(Err(e)) => e.to_string(),
+                    Err(_) => "Job timed out".to_string(),
+                };
+
+                let policy = handler.retry_policy();
+                if job.can_retry() {
+                    let delay = policy.delay(job.attempts);
+                    queue.retry(&job, delay.as_secs(), &error).await?;
+                    Self::record(
+                        audit,
+                        &job,
+                        started_at,
+                        ExecutionOutcome::Retried,
+                        Some(&error),
+                    )
+                    .await;
+                } else {
+                    queue.bury(&job, &error).await?;
+                    Self::record(
+                        audit,
+                        &job,
+                        started_at,
+                        ExecutionOutcome::Failed,
+                        Some(&error),
+                    )
+                    .await;
+                    Self::notify(events, &job, Some(&error)).await;
                 }
             }
             None => {
                 let error = format!("No handler registered for job type: {}", job_type);
-                queue.fail(job_id, &error).await?;
+                queue.bury(&job, &error).await?;
+                Self::record(
+                    audit,
+                    &job,
+                    started_at,
+                    ExecutionOutcome::Failed,
+                    Some(&error),
+                )
+                .await;
+                Self::notify(events, &job, Some(&error)).await;
             }
         }
 
         Ok(())
     }
+
+    /// Add a finished attempt to the execution audit
+    async fn record(
+        audit: Option<&ExecutionAudit>,
+        job: &Job,
+        started_at: DateTime<Utc>,
+        outcome: ExecutionOutcome,
+        error: Option<&str>,
+    ) {
+        let Some(audit) = audit else {
+            return;
+        };
+
+        let execution = JobExecution::finished(job, started_at, outcome, error);
+        if let Err(e) = audit.record(&execution).await {
+            tracing::warn!(job_id = %job.id, error = %e, "Failed to record job execution");
+        }
+    }
+
+    /// Publish the final outcome of a job
+    async fn notify(events: Option<&EventBus>, job: &Job, error: Option<&str>) {
+        let Some(events) = events else {
+            return;
+        };
+
+        let event_type = if error.is_some() {
+            "job.failed"
+        } else {
+            "job.completed"
+        };
+        let mut event = DomainEvent::new(
+            event_type,
+            serde_json::json!({
+                "job_id": job.id,
+                "job_type": job.job_type,
+                "queue": job.queue,
+                "attempts": job.attempts,
+                "error": error,
+            }),
+        )
+        .with_aggregate(job.id, "job");
+        if let Some(tenant_id) = job.tenant_id {
+            event = event.with_tenant(tenant_id);
+        }
+
+        if let Err(e) = events.publish(event).await {
+            tracing::warn!(job_id = %job.id, error = %e, "Failed to publish job event");
+        }
+    }
+}
+
+/// Seconds from `now` to the start of the next calendar month (UTC)
+fn secs_until_next_month(now: DateTime<Utc>) -> u64 {
+    let (year, month) = if now.month() == 12 {
+        (now.year() + 1, 1)
+    } else {
+        (now.year(), now.month() + 1)
+    };
+    let next = Utc
+        .with_ymd_and_hms(year, month, 1, 0, 0, 0)
+        .single()
+        .unwrap_or(now);
+    (next - now).num_seconds().max(0) as u64
 }
 
 /// Dynamic job handler trait for type erasure
 #[async_trait]
 trait JobHandlerDyn: Send + Sync {
     async fn handle_job(&self, job: &Job) -> Result<()>;
+
+    fn retry_policy(&self) -> RetryPolicy;
 }
 
 /// Typed handler wrapper
@@ -231,6 +467,10 @@ where
         let payload: P = job.payload()?;
         self.handler.handle(payload).await
     }
+
+    fn retry_policy(&self) -> RetryPolicy {
+        P::retry_policy()
+    }
 }
 
 /// Worker pool for managing multiple workers
@@ -315,5 +555,26 @@ mod tests {
         let config = WorkerConfig::default();
         assert_eq!(config.concurrency, 4);
         assert!(config.queues.contains(&"default".to_string()));
+        assert!(config.unpaused_queues.is_empty());
+    }
+
+    #[test]
+    fn test_secs_until_next_month() {
+        let now = Utc.with_ymd_and_hms(2024, 12, 31, 23, 59, 0).unwrap();
+        assert_eq!(secs_until_next_month(now), 60);
+        let now = Utc.with_ymd_and_hms(2024, 2, 28, 0, 0, 0).unwrap();
+        assert_eq!(secs_until_next_month(now), 2 * 24 * 60 * 60);
+    }
+
+    #[test]
+    fn test_pause_switch() {
+        let pause = PauseSwitch::new();
+        let shared = pause.clone();
+        assert!(!shared.is_paused());
+
+        pause.pause();
+        assert!(shared.is_paused());
+        pause.resume();
+        assert!(!shared.is_paused());
     }
 }
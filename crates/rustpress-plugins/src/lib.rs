@@ -11,6 +11,7 @@
 //! - **Discovery & Loading** - Automatic plugin discovery with hot reload
 //! - **Dependency Resolution** - DAG-based topological sort for load order
 //! - **WebAssembly Sandbox** - Secure plugin execution with Wasmtime
+//! - **WASM Plugin Host** - Runtime-installed plugins over a versioned host ABI (`wasm` feature)
 //! - **Lifecycle Management** - Activate, deactivate, upgrade, uninstall
 //! - **Settings API** - Typed settings with schema validation
 //! - **Database Migrations** - Version-tracked schema changes
@@ -43,6 +44,10 @@ pub mod loader;
 pub mod registry;
 pub mod sandbox;
 
+// WebAssembly plugin host
+#[cfg(feature = "wasm")]
+pub mod wasm_host;
+
 // Plugin manifest and discovery (Points 161-163)
 pub mod dependencies;
 pub mod discovery;
@@ -93,6 +98,8 @@ pub use dependencies::DependencyResolver;
 
 // Re-export sandbox types (Point 164)
 pub use sandbox::{SandboxError, WasmPluginSandbox, WasmSandboxConfig, WasmValue};
+#[cfg(feature = "wasm")]
+pub use wasm_host::{WasmHttpRequest, WasmHttpResponse, WasmPluginHost, HOST_ABI_VERSION};
 
 // Re-export lifecycle types (Point 165)
 pub use lifecycle::{HookRegistry, LifecycleManager, PluginState};
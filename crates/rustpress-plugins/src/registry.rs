@@ -164,6 +164,7 @@ mod tests {
             license: "MIT".to_string(),
             dependencies: vec![],
             min_rustpress_version: None,
+            required_features: vec![],
             tags: vec![],
         }
     }
@@ -0,0 +1,970 @@
+//! WebAssembly Plugin Host
+//!
+//! Runs third-party plugins compiled to WebAssembly with Wasmtime, so they
+//! can be installed at runtime without trusting native code. A plugin only
+//! reaches the host through the functions of the host ABI; it has no WASI,
+//! file system or network access.
+//!
+//! # Host ABI, version 1
+//!
+//! Values cross the boundary as UTF-8 JSON in the plugin's linear memory.
+//! A result is returned as an `i64` packing a pointer in the high 32 bits
+//! and a length in the low 32 bits; `0` means no result.
+//!
+//! The module must export:
+//!
+//! - `memory`
+//! - `rustpress_abi_version() -> i32`, the ABI version it was built against
+//! - `rustpress_alloc(len: i32) -> i32`, where the host writes its input
+//! - every hook callback and HTTP handler named in the manifest, as
+//!   `(ptr: i32, len: i32) -> i64`
+//!
+//! Action callbacks receive the action's arguments and their result is
+//! ignored. Filter callbacks receive `{"value": ..., "args": ...}` and
+//! return the filtered value. HTTP handlers receive a [`WasmHttpRequest`]
+//! and return a [`WasmHttpResponse`].
+//!
+//! The module may import from the `rustpress` module:
+//!
+//! - `rustpress_log(level: i32, ptr: i32, len: i32)`, with levels 0 (debug)
+//!   to 3 (error)
+//! - `rustpress_get_option(key_ptr: i32, key_len: i32) -> i64`, the
+//!   plugin's setting as JSON
+//! - `rustpress_set_option(key_ptr: i32, key_len: i32, ptr: i32, len: i32) -> i32`,
+//!   `0` on success and `-1` when the value is rejected
+//!
+//! Each call runs in a fresh instance with the fuel, memory and time limits
+//! of the plugin's `[wasm]` manifest section.
+
+use crate::api::ApiRegistry;
+use crate::discovery::DiscoveredPlugin;
+use crate::lifecycle::{ActionHookRegistration, FilterHookRegistration, HookRegistry};
+use crate::manifest::{HttpMethod, PluginManifest, PluginType};
+use crate::sandbox::{
+    ExecutionStats, HostContext, SandboxError, StandardHostFunctions, WasmSandboxConfig, WasmValue,
+};
+use crate::settings::SettingsManager;
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+use wasmtime::{
+    Caller, Config, Engine, ExternType, Instance, Linker, Module, ResourceLimiter, Store, Trap,
+    ValType,
+};
+
+/// Current version of the host ABI
+pub const HOST_ABI_VERSION: u32 = 1;
+
+/// ABI versions the host can run
+pub const SUPPORTED_ABI_VERSIONS: RangeInclusive<u32> = 1..=HOST_ABI_VERSION;
+
+/// Module the host functions are imported from
+pub const HOST_MODULE: &str = "rustpress";
+
+/// Host functions of ABI version 1
+const HOST_FUNCTIONS: &[&str] = &[
+    "rustpress_log",
+    "rustpress_get_option",
+    "rustpress_set_option",
+];
+
+/// How often the engine's epoch advances, the granularity of timeouts
+const EPOCH_TICK: Duration = Duration::from_millis(10);
+
+/// HTTP request passed to a plugin's handler
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WasmHttpRequest {
+    pub method: HttpMethod,
+    pub path: String,
+    /// Path parameters matched from the route
+    #[serde(default)]
+    pub params: HashMap<String, String>,
+    #[serde(default)]
+    pub query: HashMap<String, String>,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    #[serde(default)]
+    pub body: Value,
+    #[serde(default)]
+    pub user_id: Option<i64>,
+}
+
+/// HTTP response returned by a plugin's handler
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WasmHttpResponse {
+    #[serde(default = "default_status")]
+    pub status: u16,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    #[serde(default)]
+    pub body: Value,
+}
+
+fn default_status() -> u16 {
+    200
+}
+
+/// A plugin installed in the host
+struct InstalledPlugin {
+    id: String,
+    version: String,
+    abi_version: u32,
+    module: Module,
+    config: WasmSandboxConfig,
+    stats: Mutex<ExecutionStats>,
+}
+
+/// Summary of an installed plugin
+#[derive(Debug, Clone, Serialize)]
+pub struct InstalledWasmPlugin {
+    pub id: String,
+    pub version: String,
+    pub abi_version: u32,
+}
+
+/// State of the store a plugin call runs in
+struct HostState {
+    context: HostContext,
+    settings: Arc<SettingsManager>,
+    memory_limit: usize,
+    memory_exceeded: Option<usize>,
+}
+
+impl ResourceLimiter for HostState {
+    fn memory_growing(
+        &mut self,
+        _current: usize,
+        desired: usize,
+        _maximum: Option<usize>,
+    ) -> anyhow::Result<bool> {
+        if desired > self.memory_limit {
+            self.memory_exceeded = Some(desired);
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    fn table_growing(
+        &mut self,
+        _current: usize,
+        desired: usize,
+        maximum: Option<usize>,
+    ) -> anyhow::Result<bool> {
+        Ok(maximum.is_none_or(|max| desired <= max))
+    }
+}
+
+/// Advances the engine's epoch until dropped
+struct EpochTicker {
+    stop: Arc<AtomicBool>,
+}
+
+impl EpochTicker {
+    fn start(engine: Engine) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = stop.clone();
+        std::thread::Builder::new()
+            .name("wasm-epoch".to_string())
+            .spawn(move || {
+                while !stopped.load(Ordering::Relaxed) {
+                    std::thread::sleep(EPOCH_TICK);
+                    engine.increment_epoch();
+                }
+            })
+            .expect("failed to spawn the WASM epoch thread");
+        Self { stop }
+    }
+}
+
+impl Drop for EpochTicker {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Runs WASM plugins' hooks, HTTP handlers and settings access
+pub struct WasmPluginHost {
+    engine: Engine,
+    linker: Linker<HostState>,
+    plugins: RwLock<HashMap<String, Arc<InstalledPlugin>>>,
+    hooks: Arc<HookRegistry>,
+    api: Arc<ApiRegistry>,
+    settings: Arc<SettingsManager>,
+    _ticker: EpochTicker,
+}
+
+impl WasmPluginHost {
+    /// Create a host registering plugins' hooks, routes and settings in
+    /// the given registries
+    pub fn new(
+        hooks: Arc<HookRegistry>,
+        api: Arc<ApiRegistry>,
+        settings: Arc<SettingsManager>,
+    ) -> Result<Self, SandboxError> {
+        let mut config = Config::new();
+        config.consume_fuel(true);
+        config.epoch_interruption(true);
+        let engine = Engine::new(&config).map_err(|e| SandboxError::Compilation(e.to_string()))?;
+
+        let mut linker = Linker::new(&engine);
+        define_host_functions(&mut linker)?;
+
+        Ok(Self {
+            _ticker: EpochTicker::start(engine.clone()),
+            engine,
+            linker,
+            plugins: RwLock::new(HashMap::new()),
+            hooks,
+            api,
+            settings,
+        })
+    }
+
+    /// Install a plugin from its manifest and module, replacing an
+    /// installed version. The module is checked against the host ABI before
+    /// its hooks, routes and settings are registered
+    pub fn install(&self, manifest: &PluginManifest, wasm: &[u8]) -> Result<(), SandboxError> {
+        let plugin_id = &manifest.plugin.id;
+        if manifest.plugin.plugin_type != PluginType::Wasm {
+            return Err(SandboxError::Compilation(format!(
+                "{} is not a WebAssembly plugin",
+                plugin_id
+            )));
+        }
+
+        let config = WasmSandboxConfig::from(&manifest.wasm);
+        let module = Module::new(&self.engine, wasm)
+            .map_err(|e| SandboxError::Compilation(e.to_string()))?;
+        validate_imports(&module, &config)?;
+        validate_exports(&module, &callbacks(manifest))?;
+
+        let mut plugin = InstalledPlugin {
+            id: plugin_id.clone(),
+            version: manifest.plugin.version.clone(),
+            abi_version: 0,
+            module,
+            config,
+            stats: Mutex::new(ExecutionStats::default()),
+        };
+        plugin.abi_version = self.abi_version(&plugin)?;
+
+        self.uninstall(plugin_id);
+        self.register(manifest);
+        self.plugins
+            .write()
+            .insert(plugin_id.clone(), Arc::new(plugin));
+
+        info!(
+            "Installed WASM plugin {} {}",
+            plugin_id, manifest.plugin.version
+        );
+        Ok(())
+    }
+
+    /// Install a discovered plugin, reading its module from the plugin
+    /// directory
+    pub fn install_discovered(&self, plugin: &DiscoveredPlugin) -> Result<(), SandboxError> {
+        let path = plugin.path.join(&plugin.manifest.plugin.entry);
+        let wasm = std::fs::read(&path).map_err(|e| {
+            SandboxError::Compilation(format!("Cannot read {}: {}", path.display(), e))
+        })?;
+        self.install(&plugin.manifest, &wasm)
+    }
+
+    /// Uninstall a plugin, removing its hooks and routes. Its settings are
+    /// kept for a reinstall. Returns whether it was installed
+    pub fn uninstall(&self, plugin_id: &str) -> bool {
+        let removed = self.plugins.write().remove(plugin_id).is_some();
+        if removed {
+            self.hooks.remove_plugin_hooks(plugin_id);
+            self.api.unregister(plugin_id);
+            info!("Uninstalled WASM plugin {}", plugin_id);
+        }
+        removed
+    }
+
+    /// Installed plugins
+    pub fn installed(&self) -> Vec<InstalledWasmPlugin> {
+        let mut plugins: Vec<InstalledWasmPlugin> = self
+            .plugins
+            .read()
+            .values()
+            .map(|p| InstalledWasmPlugin {
+                id: p.id.clone(),
+                version: p.version.clone(),
+                abi_version: p.abi_version,
+            })
+            .collect();
+        plugins.sort_by(|a, b| a.id.cmp(&b.id));
+        plugins
+    }
+
+    pub fn is_installed(&self, plugin_id: &str) -> bool {
+        self.plugins.read().contains_key(plugin_id)
+    }
+
+    /// Execution statistics of a plugin
+    pub fn stats(&self, plugin_id: &str) -> Option<ExecutionStats> {
+        self.plugin(plugin_id).map(|p| p.stats.lock().clone())
+    }
+
+    /// Run the WASM callbacks of an action in priority order. A failing
+    /// callback doesn't stop the others; failures are returned by plugin
+    pub fn do_action(&self, hook: &str, args: &Value) -> Vec<(String, SandboxError)> {
+        let mut failures = Vec::new();
+
+        for registration in self.hooks.get_actions(hook) {
+            let Some(plugin) = self.plugin(&registration.plugin_id) else {
+                continue;
+            };
+            if let Err(e) = self.call(&plugin, &registration.callback, args, None) {
+                warn!("WASM plugin {} failed on action {}: {}", plugin.id, hook, e);
+                failures.push((plugin.id.clone(), e));
+            }
+        }
+
+        failures
+    }
+
+    /// Pass `value` through the WASM callbacks of a filter in priority
+    /// order. A failing callback leaves the value as it was
+    pub fn apply_filters(&self, hook: &str, value: Value, args: &Value) -> Value {
+        let mut value = value;
+
+        for registration in self.hooks.get_filters(hook) {
+            let Some(plugin) = self.plugin(&registration.plugin_id) else {
+                continue;
+            };
+            let input = json!({ "value": value, "args": args });
+            match self.call(&plugin, &registration.callback, &input, None) {
+                Ok(Some(filtered)) => value = filtered,
+                Ok(None) => {}
+                Err(e) => warn!("WASM plugin {} failed on filter {}: {}", plugin.id, hook, e),
+            }
+        }
+
+        value
+    }
+
+    /// Handle a request to a route registered by a WASM plugin, or `None`
+    /// when no installed plugin serves the path
+    pub fn handle_request(
+        &self,
+        request: WasmHttpRequest,
+    ) -> Option<Result<WasmHttpResponse, SandboxError>> {
+        let (route, params) = self.api.match_route(&request.path, request.method)?;
+        let plugin = self.plugin(&route.plugin_id)?;
+
+        let request = WasmHttpRequest { params, ..request };
+        let input = serde_json::to_value(&request).ok()?;
+        let result = self
+            .call(&plugin, &route.handler, &input, request.user_id)
+            .and_then(|output| match output {
+                Some(output) => {
+                    serde_json::from_value(output).map_err(|e| SandboxError::TypeMismatch {
+                        expected: "HTTP response".to_string(),
+                        got: e.to_string(),
+                    })
+                }
+                None => Ok(WasmHttpResponse {
+                    status: 204,
+                    headers: HashMap::new(),
+                    body: Value::Null,
+                }),
+            });
+
+        Some(result)
+    }
+
+    /// Call an exported callback of a plugin directly
+    pub fn call_export(
+        &self,
+        plugin_id: &str,
+        export: &str,
+        input: &Value,
+    ) -> Result<Option<Value>, SandboxError> {
+        let plugin = self
+            .plugin(plugin_id)
+            .ok_or_else(|| SandboxError::FunctionNotFound(format!("{}::{}", plugin_id, export)))?;
+        self.call(&plugin, export, input, None)
+    }
+
+    fn plugin(&self, plugin_id: &str) -> Option<Arc<InstalledPlugin>> {
+        self.plugins.read().get(plugin_id).cloned()
+    }
+
+    /// Register the hooks, routes and settings schema of a manifest
+    fn register(&self, manifest: &PluginManifest) {
+        let plugin_id = &manifest.plugin.id;
+
+        for action in &manifest.hooks.actions {
+            self.hooks.add_action(
+                &action.hook,
+                ActionHookRegistration {
+                    hook_name: action.hook.clone(),
+                    plugin_id: plugin_id.clone(),
+                    callback: action.callback.clone(),
+                    priority: action.priority,
+                },
+            );
+        }
+        for filter in &manifest.hooks.filters {
+            self.hooks.add_filter(
+                &filter.hook,
+                FilterHookRegistration {
+                    hook_name: filter.hook.clone(),
+                    plugin_id: plugin_id.clone(),
+                    callback: filter.callback.clone(),
+                    priority: filter.priority,
+                },
+            );
+        }
+        self.api.register_from_manifest(plugin_id, &manifest.api);
+
+        // Keep stored values that are still valid under the new schema
+        let stored = self.settings.get_all(plugin_id);
+        self.settings.register_schema(plugin_id, &manifest.settings);
+        for (key, value) in stored.into_iter().flat_map(|s| s.values) {
+            if let Err(e) = self.settings.set(plugin_id, &key, value) {
+                debug!("Dropped setting {} of {}: {}", key, plugin_id, e);
+            }
+        }
+    }
+
+    /// Store with the plugin's limits for one call
+    fn store(
+        &self,
+        plugin: &InstalledPlugin,
+        user_id: Option<i64>,
+    ) -> Result<Store<HostState>, SandboxError> {
+        let mut store = Store::new(
+            &self.engine,
+            HostState {
+                context: HostContext {
+                    plugin_id: plugin.id.clone(),
+                    user_id,
+                    site_id: None,
+                    request_id: None,
+                },
+                settings: self.settings.clone(),
+                memory_limit: plugin.config.max_memory as usize,
+                memory_exceeded: None,
+            },
+        );
+        store.limiter(|state| state);
+        store
+            .set_fuel(plugin.config.max_fuel.unwrap_or(u64::MAX))
+            .map_err(|e| SandboxError::Instantiation(e.to_string()))?;
+        let ticks = plugin.config.max_execution_time.as_millis() / EPOCH_TICK.as_millis();
+        store.set_epoch_deadline(ticks.max(1) as u64);
+        Ok(store)
+    }
+
+    fn instantiate(
+        &self,
+        plugin: &InstalledPlugin,
+        store: &mut Store<HostState>,
+    ) -> Result<Instance, SandboxError> {
+        self.linker
+            .instantiate(&mut *store, &plugin.module)
+            .map_err(|e| trap_error(e, store, plugin))
+    }
+
+    /// ABI version the module was built against, checked against the
+    /// versions the host runs
+    fn abi_version(&self, plugin: &InstalledPlugin) -> Result<u32, SandboxError> {
+        let mut store = self.store(plugin, None)?;
+        let instance = self.instantiate(plugin, &mut store)?;
+        let version = instance
+            .get_typed_func::<(), i32>(&mut store, "rustpress_abi_version")
+            .and_then(|f| f.call(&mut store, ()))
+            .map_err(|e| trap_error(e, &store, plugin))? as u32;
+
+        if !SUPPORTED_ABI_VERSIONS.contains(&version) {
+            return Err(SandboxError::Instantiation(format!(
+                "{} targets host ABI version {}, supported versions are {} to {}",
+                plugin.id,
+                version,
+                SUPPORTED_ABI_VERSIONS.start(),
+                SUPPORTED_ABI_VERSIONS.end()
+            )));
+        }
+        Ok(version)
+    }
+
+    /// Run `export` with `input` in a fresh instance
+    fn call(
+        &self,
+        plugin: &InstalledPlugin,
+        export: &str,
+        input: &Value,
+        user_id: Option<i64>,
+    ) -> Result<Option<Value>, SandboxError> {
+        let start = Instant::now();
+        let mut store = self.store(plugin, user_id)?;
+        let result = self.run(plugin, &mut store, export, input);
+
+        let elapsed = start.elapsed();
+        let fuel = plugin
+            .config
+            .max_fuel
+            .zip(store.get_fuel().ok())
+            .map_or(0, |(max, left)| max - left);
+        let mut stats = plugin.stats.lock();
+        stats.total_calls += 1;
+        stats.last_call = Some(chrono::Utc::now());
+        stats.total_execution_time += elapsed;
+        stats.max_execution_time = stats.max_execution_time.max(elapsed);
+        stats.total_fuel_consumed += fuel;
+        match &result {
+            Err(SandboxError::Timeout) => stats.timeout_count += 1,
+            Err(SandboxError::MemoryLimit { .. }) => stats.oom_count += 1,
+            Err(_) => stats.error_count += 1,
+            Ok(_) => {}
+        }
+
+        result
+    }
+
+    fn run(
+        &self,
+        plugin: &InstalledPlugin,
+        store: &mut Store<HostState>,
+        export: &str,
+        input: &Value,
+    ) -> Result<Option<Value>, SandboxError> {
+        let instance = self.instantiate(plugin, store)?;
+        let callback = instance
+            .get_typed_func::<(i32, i32), i64>(&mut *store, export)
+            .map_err(|_| SandboxError::FunctionNotFound(export.to_string()))?;
+        let memory = instance
+            .get_memory(&mut *store, "memory")
+            .ok_or_else(|| SandboxError::Instantiation("module exports no memory".to_string()))?;
+        let alloc = instance
+            .get_typed_func::<i32, i32>(&mut *store, "rustpress_alloc")
+            .map_err(|_| SandboxError::FunctionNotFound("rustpress_alloc".to_string()))?;
+
+        let input =
+            serde_json::to_vec(input).map_err(|e| SandboxError::Execution(e.to_string()))?;
+        let ptr = alloc
+            .call(&mut *store, input.len() as i32)
+            .map_err(|e| trap_error(e, store, plugin))?;
+        memory
+            .write(&mut *store, ptr as u32 as usize, &input)
+            .map_err(|e| SandboxError::Execution(e.to_string()))?;
+
+        debug!("Calling {}::{}", plugin.id, export);
+        let packed = callback
+            .call(&mut *store, (ptr, input.len() as i32))
+            .map_err(|e| trap_error(e, store, plugin))?;
+        if packed == 0 {
+            return Ok(None);
+        }
+
+        let output = read_packed(memory.data(&*store), packed)?;
+        serde_json::from_slice(output)
+            .map(Some)
+            .map_err(|e| SandboxError::TypeMismatch {
+                expected: "JSON".to_string(),
+                got: e.to_string(),
+            })
+    }
+}
+
+impl std::fmt::Debug for WasmPluginHost {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WasmPluginHost")
+            .field("plugins", &self.plugins.read().keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// Callbacks and handlers a manifest expects the module to export
+fn callbacks(manifest: &PluginManifest) -> Vec<&str> {
+    let hooks = &manifest.hooks;
+    hooks
+        .actions
+        .iter()
+        .map(|a| a.callback.as_str())
+        .chain(hooks.filters.iter().map(|f| f.callback.as_str()))
+        .chain(manifest.api.endpoints.iter().map(|e| e.handler.as_str()))
+        .collect()
+}
+
+/// Only host ABI functions the plugin is allowed may be imported
+fn validate_imports(module: &Module, config: &WasmSandboxConfig) -> Result<(), SandboxError> {
+    for import in module.imports() {
+        let name = format!("{}::{}", import.module(), import.name());
+        let is_host_function = import.module() == HOST_MODULE
+            && HOST_FUNCTIONS.contains(&import.name())
+            && matches!(import.ty(), ExternType::Func(_));
+        let allowed = config.allowed_imports.is_empty()
+            || config.allowed_imports.iter().any(|a| a == import.name());
+        if !is_host_function || !allowed {
+            return Err(SandboxError::InvalidImport(name));
+        }
+    }
+    Ok(())
+}
+
+fn validate_exports(module: &Module, callbacks: &[&str]) -> Result<(), SandboxError> {
+    let exports: HashMap<&str, ExternType> = module.exports().map(|e| (e.name(), e.ty())).collect();
+    let has_func =
+        |name: &str, params: &[fn(&ValType) -> bool], results: &[fn(&ValType) -> bool]| {
+            matches!(exports.get(name), Some(ExternType::Func(ty))
+            if ty.params().len() == params.len()
+                && ty.params().zip(params).all(|(p, check)| check(&p))
+                && ty.results().len() == results.len()
+                && ty.results().zip(results).all(|(r, check)| check(&r)))
+        };
+    let i32_type: fn(&ValType) -> bool = |t| matches!(t, ValType::I32);
+    let i64_type: fn(&ValType) -> bool = |t| matches!(t, ValType::I64);
+
+    if !matches!(exports.get("memory"), Some(ExternType::Memory(_))) {
+        return Err(SandboxError::FunctionNotFound("memory".to_string()));
+    }
+    if !has_func("rustpress_abi_version", &[], &[i32_type]) {
+        return Err(SandboxError::FunctionNotFound(
+            "rustpress_abi_version".to_string(),
+        ));
+    }
+    if !has_func("rustpress_alloc", &[i32_type], &[i32_type]) {
+        return Err(SandboxError::FunctionNotFound(
+            "rustpress_alloc".to_string(),
+        ));
+    }
+    for callback in callbacks {
+        if !has_func(callback, &[i32_type, i32_type], &[i64_type]) {
+            return Err(SandboxError::FunctionNotFound((*callback).to_string()));
+        }
+    }
+    Ok(())
+}
+
+/// Bytes a packed pointer and length refer to
+fn read_packed(memory: &[u8], packed: i64) -> Result<&[u8], SandboxError> {
+    let ptr = (packed as u64 >> 32) as usize;
+    let len = (packed as u64 & 0xffff_ffff) as usize;
+    memory
+        .get(ptr..ptr.saturating_add(len))
+        .ok_or_else(|| SandboxError::Execution("result is out of bounds".to_string()))
+}
+
+fn read_string(memory: &[u8], ptr: i32, len: i32) -> anyhow::Result<String> {
+    let start = ptr as u32 as usize;
+    let bytes = memory
+        .get(start..start.saturating_add(len as u32 as usize))
+        .ok_or_else(|| anyhow::anyhow!("string is out of bounds"))?;
+    Ok(String::from_utf8(bytes.to_vec())?)
+}
+
+/// Copy `bytes` into the guest's memory, returning them packed
+fn write_guest(caller: &mut Caller<'_, HostState>, bytes: &[u8]) -> anyhow::Result<i64> {
+    let alloc = caller
+        .get_export("rustpress_alloc")
+        .and_then(|e| e.into_func())
+        .ok_or_else(|| anyhow::anyhow!("module exports no rustpress_alloc"))?
+        .typed::<i32, i32>(&*caller)?;
+    let ptr = alloc.call(&mut *caller, bytes.len() as i32)?;
+    let memory = guest_memory(caller)?;
+    memory.write(&mut *caller, ptr as u32 as usize, bytes)?;
+    Ok(((ptr as u32 as i64) << 32) | bytes.len() as i64)
+}
+
+fn guest_memory(caller: &mut Caller<'_, HostState>) -> anyhow::Result<wasmtime::Memory> {
+    caller
+        .get_export("memory")
+        .and_then(|e| e.into_memory())
+        .ok_or_else(|| anyhow::anyhow!("module exports no memory"))
+}
+
+fn define_host_functions(linker: &mut Linker<HostState>) -> Result<(), SandboxError> {
+    let error = |e: anyhow::Error| SandboxError::HostFunction(e.to_string());
+
+    linker
+        .func_wrap(
+            HOST_MODULE,
+            "rustpress_log",
+            |mut caller: Caller<'_, HostState>,
+             level: i32,
+             ptr: i32,
+             len: i32|
+             -> anyhow::Result<()> {
+                let memory = guest_memory(&mut caller)?;
+                let message = read_string(memory.data(&caller), ptr, len)?;
+                let context = caller.data().context.clone();
+                StandardHostFunctions::log(
+                    &context,
+                    vec![WasmValue::I32(level), WasmValue::String(message)],
+                )
+                .map_err(anyhow::Error::msg)?;
+                Ok(())
+            },
+        )
+        .map_err(error)?;
+
+    linker
+        .func_wrap(
+            HOST_MODULE,
+            "rustpress_get_option",
+            |mut caller: Caller<'_, HostState>, ptr: i32, len: i32| -> anyhow::Result<i64> {
+                let memory = guest_memory(&mut caller)?;
+                let key = read_string(memory.data(&caller), ptr, len)?;
+                let state = caller.data();
+                let value: Value = state
+                    .settings
+                    .get_or_default(&state.context.plugin_id, &key)
+                    .into();
+                if value.is_null() {
+                    return Ok(0);
+                }
+                write_guest(&mut caller, &serde_json::to_vec(&value)?)
+            },
+        )
+        .map_err(error)?;
+
+    linker
+        .func_wrap(
+            HOST_MODULE,
+            "rustpress_set_option",
+            |mut caller: Caller<'_, HostState>,
+             key_ptr: i32,
+             key_len: i32,
+             ptr: i32,
+             len: i32|
+             -> anyhow::Result<i32> {
+                let memory = guest_memory(&mut caller)?;
+                let key = read_string(memory.data(&caller), key_ptr, key_len)?;
+                let Ok(value) =
+                    serde_json::from_str::<Value>(&read_string(memory.data(&caller), ptr, len)?)
+                else {
+                    return Ok(-1);
+                };
+                let state = caller.data();
+                match state
+                    .settings
+                    .set(&state.context.plugin_id, &key, value.into())
+                {
+                    Ok(()) => Ok(0),
+                    Err(e) => {
+                        debug!("Plugin {} setting rejected: {}", state.context.plugin_id, e);
+                        Ok(-1)
+                    }
+                }
+            },
+        )
+        .map_err(error)?;
+
+    Ok(())
+}
+
+/// Sandbox error for a failed instantiation or call
+fn trap_error(
+    e: anyhow::Error,
+    store: &Store<HostState>,
+    plugin: &InstalledPlugin,
+) -> SandboxError {
+    if let Some(used) = store.data().memory_exceeded {
+        return SandboxError::MemoryLimit {
+            used: used as u64,
+            limit: plugin.config.max_memory,
+        };
+    }
+    match e.downcast_ref::<Trap>() {
+        Some(Trap::OutOfFuel) => SandboxError::FuelExhausted,
+        Some(Trap::Interrupt) => SandboxError::Timeout,
+        Some(trap) => SandboxError::Trap(trap.to_string()),
+        None => SandboxError::Execution(format!("{:#}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"
+[plugin]
+id = "greeter"
+name = "Greeter"
+version = "1.0.0"
+
+[[hooks.actions]]
+hook = "init"
+callback = "on_init"
+
+[[hooks.filters]]
+hook = "the_title"
+callback = "filter_title"
+
+[settings.schema.greeting]
+setting_type = "string"
+label = "Greeting"
+
+[api]
+namespace = "greeter"
+version = "v1"
+
+[[api.endpoints]]
+path = "/hello/:name"
+method = "GET"
+handler = "handle_hello"
+
+[wasm]
+memory_limit = 2
+timeout_ms = 200
+fuel_limit = 1000000
+"#;
+
+    /// Guest with a bump allocator, writing constant JSON results
+    const GUEST: &str = r#"
+(module
+  (import "rustpress" "rustpress_get_option" (func $get_option (param i32 i32) (result i64)))
+  (import "rustpress" "rustpress_set_option" (func $set_option (param i32 i32 i32 i32) (result i32)))
+  (memory (export "memory") 1)
+  (global $next (mut i32) (i32.const 1024))
+  (data (i32.const 16) "greeting")
+  (data (i32.const 32) "\"hello\"")
+  (data (i32.const 64) "\"Filtered\"")
+  (data (i32.const 128) "{\"status\":201,\"body\":{\"ok\":true}}")
+  (func (export "rustpress_abi_version") (result i32) (i32.const 1))
+  (func (export "rustpress_alloc") (param $len i32) (result i32)
+    (local $ptr i32)
+    (local.set $ptr (global.get $next))
+    (global.set $next (i32.add (global.get $next) (local.get $len)))
+    (local.get $ptr))
+  (func (export "on_init") (param i32 i32) (result i64)
+    (drop (call $set_option (i32.const 16) (i32.const 8) (i32.const 32) (i32.const 7)))
+    (i64.const 0))
+  (func (export "filter_title") (param i32 i32) (result i64)
+    (i64.or (i64.shl (i64.const 64) (i64.const 32)) (i64.const 10)))
+  (func (export "handle_hello") (param i32 i32) (result i64)
+    (i64.or (i64.shl (i64.const 128) (i64.const 32)) (i64.const 33)))
+  (func (export "read_greeting") (param i32 i32) (result i64)
+    (call $get_option (i32.const 16) (i32.const 8)))
+  (func (export "spin") (param i32 i32) (result i64)
+    (loop $forever (br $forever))
+    (i64.const 0))
+  (func (export "grow") (param i32 i32) (result i64)
+    (drop (memory.grow (i32.const 64)))
+    (if (i32.eq (memory.size) (i32.const 1)) (then unreachable))
+    (i64.const 0)))
+"#;
+
+    fn host() -> WasmPluginHost {
+        WasmPluginHost::new(
+            Arc::new(HookRegistry::new()),
+            Arc::new(ApiRegistry::new()),
+            Arc::new(SettingsManager::new()),
+        )
+        .unwrap()
+    }
+
+    fn install(host: &WasmPluginHost, manifest: &str, guest: &str) -> Result<(), SandboxError> {
+        let manifest = PluginManifest::from_toml(manifest).unwrap();
+        host.install(&manifest, &wat::parse_str(guest).unwrap())
+    }
+
+    #[test]
+    fn test_hooks_and_settings() {
+        let host = host();
+        install(&host, MANIFEST, GUEST).unwrap();
+
+        assert!(host.do_action("init", &json!([])).is_empty());
+        assert_eq!(
+            host.settings.get("greeter", "greeting").map(Value::from),
+            Some(json!("hello"))
+        );
+        assert_eq!(
+            host.call_export("greeter", "read_greeting", &Value::Null)
+                .unwrap(),
+            Some(json!("hello"))
+        );
+        assert_eq!(
+            host.apply_filters("the_title", json!("Title"), &json!([])),
+            json!("Filtered")
+        );
+        assert_eq!(host.stats("greeter").unwrap().total_calls, 3);
+    }
+
+    #[test]
+    fn test_handle_request() {
+        let host = host();
+        install(&host, MANIFEST, GUEST).unwrap();
+
+        let request = WasmHttpRequest {
+            method: HttpMethod::Get,
+            path: "/wp-json/greeter/v1/hello/ada".to_string(),
+            params: HashMap::new(),
+            query: HashMap::new(),
+            headers: HashMap::new(),
+            body: Value::Null,
+            user_id: None,
+        };
+        let response = host.handle_request(request.clone()).unwrap().unwrap();
+        assert_eq!(response.status, 201);
+        assert_eq!(response.body, json!({ "ok": true }));
+
+        assert!(host.uninstall("greeter"));
+        assert!(host.handle_request(request).is_none());
+        assert!(host.hooks.get_actions("init").is_empty());
+    }
+
+    #[test]
+    fn test_limits() {
+        let host = host();
+        install(&host, MANIFEST, GUEST).unwrap();
+
+        assert!(matches!(
+            host.call_export("greeter", "spin", &Value::Null),
+            Err(SandboxError::FuelExhausted)
+        ));
+        assert!(matches!(
+            host.call_export("greeter", "grow", &Value::Null),
+            Err(SandboxError::MemoryLimit { .. })
+        ));
+
+        let unmetered = MANIFEST.replace("fuel_limit = 1000000", "");
+        install(&host, &unmetered, GUEST).unwrap();
+        assert!(matches!(
+            host.call_export("greeter", "spin", &Value::Null),
+            Err(SandboxError::Timeout)
+        ));
+        assert_eq!(host.stats("greeter").unwrap().timeout_count, 1);
+    }
+
+    #[test]
+    fn test_rejects_incompatible_modules() {
+        let host = host();
+
+        let wasi = GUEST.replace(
+            r#"(import "rustpress" "rustpress_get_option""#,
+            r#"(import "wasi_snapshot_preview1" "fd_write""#,
+        );
+        assert!(matches!(
+            install(&host, MANIFEST, &wasi),
+            Err(SandboxError::InvalidImport(_))
+        ));
+
+        let missing = GUEST.replace(r#"(export "filter_title")"#, "");
+        assert!(matches!(
+            install(&host, MANIFEST, &missing),
+            Err(SandboxError::FunctionNotFound(f)) if f == "filter_title"
+        ));
+
+        let future = GUEST.replace(
+            "(result i32) (i32.const 1))",
+            "(result i32) (i32.const 99))",
+        );
+        assert!(matches!(
+            install(&host, MANIFEST, &future),
+            Err(SandboxError::Instantiation(_))
+        ));
+        assert!(!host.is_installed("greeter"));
+    }
+}
@@ -1,23 +1,28 @@
 //! Main application struct and server setup.
 
+use axum::body::Body;
+use axum::http::Request;
 use axum::{middleware as axum_middleware, Router};
+use rustpress_core::config::TenantIdentification;
 use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::net::TcpListener;
-use tower::ServiceBuilder;
+use tower::{Layer, ServiceExt};
 use tower_http::trace::TraceLayer;
 use tracing::{info, warn};
 
 use crate::error::HttpError;
 use crate::metrics::Metrics;
 use crate::middleware::{
-    api_version, body_limit, compression_layer, cors_layer, rate_limit, request_id,
-    request_logging, security_headers, tenant_identification,
+    api_version, body_limit, cache_policy, captcha_verification, compliance, compression_layer,
+    cors_layer, fault_scope, http_signatures, page_cache, rate_limit, read_only, redirects,
+    request_id, request_logging, security_headers, site_path_prefix, tenant_identification,
 };
+use crate::middleware_stack::{GroupPlan, MiddlewareKind, MiddlewarePlan};
 use crate::routes::create_router;
 use crate::security::{
-    bot_detection::{bot_detection, BotDetectionConfig, BotDetectionMiddleware},
+    bot_detection::{bot_detection, page_view_analytics, BotDetectionMiddleware},
     content_security::{content_security, ContentSecurityConfig, ContentSecurityMiddleware},
     fingerprint::{fingerprint, FingerprintConfig, FingerprintMiddleware},
     request_validation::{request_validation, SecurityConfig, SecurityMiddleware},
@@ -28,6 +33,7 @@ use crate::shutdown::{
     ShutdownPhase,
 };
 use crate::state::AppState;
+use crate::transaction::request_transaction;
 
 /// Main application struct
 pub struct App {
@@ -45,16 +51,18 @@ pub struct App {
 impl App {
     /// Create a new application instance
     pub fn new(state: AppState) -> Self {
+        let audit_logger = SecurityAuditLogger::new(SecurityAuditConfig::default())
+            .with_geoip(state.geoip.clone());
         Self {
+            bot_detection: state.bot_detection.clone(),
             state,
             metrics: Arc::new(Metrics::new()),
             shutdown_controller: ShutdownController::with_default_timeout(),
             // Initialize security middleware with default configs
             security_middleware: SecurityMiddleware::new(SecurityConfig::default()),
             content_security: ContentSecurityMiddleware::new(ContentSecurityConfig::default()),
-            bot_detection: BotDetectionMiddleware::new(BotDetectionConfig::default()),
             fingerprint: FingerprintMiddleware::new(FingerprintConfig::default()),
-            audit_logger: SecurityAuditLogger::new(SecurityAuditConfig::default()),
+            audit_logger,
         }
     }
 
@@ -80,69 +88,176 @@ impl App {
     }
 
     /// Build the router with all middleware
+    ///
+    /// Each route group configured under `[middleware]` gets its own copy
+    /// of the routes layered with its stack; requests are dispatched to
+    /// the group with the longest matching path prefix.
+    ///
+    /// With sites of a network told apart by path, the site prefix is
+    /// stripped before routing and grouping, so sites share the main
+    /// site's routes.
     pub fn build_router(&self) -> Router {
-        let router = create_router(self.state.clone());
-
-        // Apply middleware stack (order matters - last added is first executed)
-        // Execution order: Compression -> Tracing -> Request ID -> Security Audit ->
-        // Fingerprint -> Bot Detection -> Logging -> Security Headers ->
-        // Request Validation -> Content Security -> CORS -> Body Limit ->
-        // API Version -> Rate Limit -> Tenant ID -> Route Handler
-        router
-            .layer(
-                ServiceBuilder::new()
-                    // Compression
-                    .layer(compression_layer())
-                    // Tracing
-                    .layer(TraceLayer::new_for_http()),
-            )
-            // Request ID (first, so all subsequent middleware can use it)
-            .layer(axum_middleware::from_fn(request_id))
-            // Security audit logging (captures all security events)
-            .layer(axum_middleware::from_fn_with_state(
-                self.audit_logger.clone(),
-                security_audit,
-            ))
-            // Request fingerprinting (tracks client behavior)
-            .layer(axum_middleware::from_fn_with_state(
-                self.fingerprint.clone(),
-                fingerprint,
-            ))
-            // Bot detection (blocks automated attacks)
-            .layer(axum_middleware::from_fn_with_state(
+        let router = self.grouped_router();
+        let multitenancy = &self.state.config.multitenancy;
+        if !multitenancy.enabled || multitenancy.identification != TenantIdentification::Path {
+            return router;
+        }
+        let prefix = axum_middleware::from_fn_with_state(self.state.clone(), site_path_prefix);
+        Router::new().fallback_service(prefix.layer(router))
+    }
+
+    /// Routes dispatched to their middleware group
+    fn grouped_router(&self) -> Router {
+        let plan = match MiddlewarePlan::from_config(&self.state.config.middleware) {
+            Ok(plan) => plan,
+            Err(e) => {
+                warn!(
+                    "Invalid middleware configuration, using the built-in order: {}",
+                    e
+                );
+                MiddlewarePlan::default()
+            }
+        };
+
+        // Routes are built once; every group layers its stack over a clone
+        let routes = create_router(self.state.clone());
+        if plan.groups.is_empty() {
+            return self.layered_router(routes, &plan.default.stack);
+        }
+
+        let default = self.layered_router(routes.clone(), &plan.default.stack);
+        let groups: Arc<Vec<(GroupPlan, Router)>> = Arc::new(
+            plan.groups
+                .iter()
+                .map(|group| {
+                    (
+                        group.clone(),
+                        self.layered_router(routes.clone(), &group.stack),
+                    )
+                })
+                .collect(),
+        );
+        let plan = Arc::new(plan);
+
+        Router::new().fallback_service(tower::service_fn(move |request: Request<Body>| {
+            let group = plan.resolve(request.uri().path()).name.clone();
+            let router = groups
+                .iter()
+                .find(|(plan, _)| plan.name == group)
+                .map_or_else(|| default.clone(), |(_, router)| router.clone());
+            router.oneshot(request)
+        }))
+    }
+
+    /// `routes` wrapped in `stack`, outermost first
+    fn layered_router(&self, routes: Router, stack: &[MiddlewareKind]) -> Router {
+        stack
+            .iter()
+            .rev()
+            .fold(routes, |router, kind| self.layer(router, *kind))
+    }
+
+    /// Wrap `router` in one middleware
+    fn layer(&self, router: Router, kind: MiddlewareKind) -> Router {
+        match kind {
+            // Route tagging for fault injection rules
+            MiddlewareKind::FaultScope => router.layer(axum_middleware::from_fn_with_state(
+                self.state.clone(),
+                fault_scope,
+            )),
+            MiddlewareKind::TenantIdentification => router.layer(
+                axum_middleware::from_fn_with_state(self.state.clone(), tenant_identification),
+            ),
+            // Stored redirects for public paths, answered before routing
+            MiddlewareKind::Redirects => router.layer(axum_middleware::from_fn_with_state(
+                self.state.clone(),
+                redirects,
+            )),
+            // Full-page cache for anonymous public pages
+            MiddlewareKind::PageCache => router.layer(axum_middleware::from_fn_with_state(
+                self.state.clone(),
+                page_cache,
+            )),
+            // Edge cache headers for public responses
+            MiddlewareKind::CachePolicy => router.layer(axum_middleware::from_fn_with_state(
+                self.state.clone(),
+                cache_policy,
+            )),
+            // Regional compliance rules (blocking, age gates) for public pages
+            MiddlewareKind::Compliance => router.layer(axum_middleware::from_fn_with_state(
+                self.state.clone(),
+                compliance,
+            )),
+            // CAPTCHA verification for public submissions
+            MiddlewareKind::Captcha => router.layer(axum_middleware::from_fn_with_state(
+                self.state.clone(),
+                captcha_verification,
+            )),
+            // Bot detection (classifies traffic for the layers inside it,
+            // optionally blocks bots)
+            MiddlewareKind::BotDetection => router.layer(axum_middleware::from_fn_with_state(
                 self.bot_detection.clone(),
                 bot_detection,
-            ))
-            // Request logging
-            .layer(axum_middleware::from_fn(request_logging))
-            // Security headers (enhanced with COEP, COOP, CORP, Permissions-Policy)
-            .layer(axum_middleware::from_fn(security_headers))
-            // Request validation (SQL injection, XSS, path traversal protection)
-            .layer(axum_middleware::from_fn_with_state(
-                self.security_middleware.clone(),
-                request_validation,
-            ))
+            )),
+            // First-party page views, humans only
+            MiddlewareKind::PageViewAnalytics => router.layer(axum_middleware::from_fn_with_state(
+                self.bot_detection.clone(),
+                page_view_analytics,
+            )),
+            // Rate limiting, with separate buckets per traffic class
+            MiddlewareKind::RateLimit => router.layer(axum_middleware::from_fn_with_state(
+                self.state.clone(),
+                rate_limit,
+            )),
+            // Read-only maintenance mode (refuses writes)
+            MiddlewareKind::ReadOnly => router.layer(axum_middleware::from_fn_with_state(
+                self.state.clone(),
+                read_only,
+            )),
+            MiddlewareKind::ApiVersion => router.layer(axum_middleware::from_fn(api_version)),
+            MiddlewareKind::BodyLimit => router.layer(axum_middleware::from_fn(body_limit)),
+            MiddlewareKind::Cors => router.layer(cors_layer()),
             // Content security (JSON depth, content-type validation)
-            .layer(axum_middleware::from_fn_with_state(
+            MiddlewareKind::ContentSecurity => router.layer(axum_middleware::from_fn_with_state(
                 self.content_security.clone(),
                 content_security,
-            ))
-            // CORS
-            .layer(cors_layer())
-            // Body size limit
-            .layer(axum_middleware::from_fn(body_limit))
-            // API versioning
-            .layer(axum_middleware::from_fn(api_version))
-            // Rate limiting
-            .layer(axum_middleware::from_fn_with_state(
+            )),
+            // Request validation (SQL injection, XSS, path traversal protection)
+            MiddlewareKind::RequestValidation => router.layer(axum_middleware::from_fn_with_state(
+                self.security_middleware.clone(),
+                request_validation,
+            )),
+            // Security headers (enhanced with COEP, COOP, CORP, Permissions-Policy)
+            MiddlewareKind::SecurityHeaders => {
+                router.layer(axum_middleware::from_fn(security_headers))
+            }
+            MiddlewareKind::RequestLogging => {
+                router.layer(axum_middleware::from_fn(request_logging))
+            }
+            // Request fingerprinting (tracks client behavior)
+            MiddlewareKind::Fingerprint => router.layer(axum_middleware::from_fn_with_state(
+                self.fingerprint.clone(),
+                fingerprint,
+            )),
+            // Security audit logging (captures all security events)
+            MiddlewareKind::SecurityAudit => router.layer(axum_middleware::from_fn_with_state(
+                self.audit_logger.clone(),
+                security_audit,
+            )),
+            MiddlewareKind::RequestId => router.layer(axum_middleware::from_fn(request_id)),
+            // HTTP message signatures of server-to-server requests
+            MiddlewareKind::HttpSignatures => router.layer(axum_middleware::from_fn_with_state(
                 self.state.clone(),
-                rate_limit,
-            ))
-            // Tenant identification
-            .layer(axum_middleware::from_fn_with_state(
+                http_signatures,
+            )),
+            // Opened per mutating request, committed on 2xx
+            MiddlewareKind::Transactions => router.layer(axum_middleware::from_fn_with_state(
                 self.state.clone(),
-                tenant_identification,
-            ))
+                request_transaction,
+            )),
+            MiddlewareKind::Compression => router.layer(compression_layer()),
+            MiddlewareKind::Tracing => router.layer(TraceLayer::new_for_http()),
+        }
     }
 
     /// Run the HTTP server
@@ -165,10 +280,15 @@ impl App {
         // Register shutdown handlers
         let state_clone = self.state.clone();
         shutdown_executor.register(ShutdownPhase::FlushCaches, move || {
-            let _state = state_clone.clone();
+            let state = state_clone.clone();
             async move {
                 info!("Flushing caches...");
-                // Cache flush logic would go here
+                if let Err(e) = state.public_api.flush_usage().await {
+                    warn!("Failed to flush public API usage: {}", e);
+                }
+                if let Err(e) = state.usage.flush_usage().await {
+                    warn!("Failed to flush site usage: {}", e);
+                }
             }
         });
 
@@ -182,9 +302,12 @@ impl App {
         });
 
         // Run server with graceful shutdown
-        axum::serve(listener, router)
-            .with_graceful_shutdown(graceful_shutdown(shutdown_controller))
-            .await?;
+        axum::serve(
+            listener,
+            router.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .with_graceful_shutdown(graceful_shutdown(shutdown_controller))
+        .await?;
 
         // Execute ordered shutdown
         shutdown_executor.execute().await;
@@ -267,6 +390,84 @@ pub async fn serve(state: AppState, addr: &str) -> Result<(), Box<dyn std::error
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::security::TrafficClass;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use rustpress_core::config::AppConfig;
+    use rustpress_core::config::TenantIdentification;
+    use tower::{Layer, ServiceExt};
+
+    fn api_request(user_agent: &str) -> Request<Body> {
+        Request::builder()
+            .uri("/api/v1/does-not-exist")
+            .header("user-agent", user_agent)
+            .header("accept", "application/json")
+            .header("accept-language", "en")
+            .header("accept-encoding", "gzip")
+            .header("x-forwarded-for", "203.0.113.7")
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_uses_bot_classification() {
+        let mut config = AppConfig::default();
+        config.rate_limit.requests_per_window = 100;
+        config.rate_limit.known_bot_requests_per_window = 1;
+        let app = App::new(AppState::for_tests(config));
+        let router = app.build_router();
+
+        let bot = "Mozilla/5.0 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)";
+        let first = router.clone().oneshot(api_request(bot)).await.unwrap();
+        assert_ne!(first.status(), StatusCode::TOO_MANY_REQUESTS);
+        let second = router.clone().oneshot(api_request(bot)).await.unwrap();
+        assert_eq!(second.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        // Humans behind the same address keep their own allowance
+        let human = router
+            .oneshot(api_request("Mozilla/5.0 Firefox/120.0"))
+            .await
+            .unwrap();
+        assert_ne!(human.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        let stats = app.bot_detection.stats();
+        assert_eq!(stats.requests[&TrafficClass::KnownBot], 2);
+        assert_eq!(stats.requests[&TrafficClass::Human], 1);
+    }
+
+    #[tokio::test]
+    async fn test_route_groups_use_their_own_stack() {
+        let mut config = AppConfig::default();
+        config.rate_limit.known_bot_requests_per_window = 1;
+        config.middleware.groups = vec![rustpress_core::config::MiddlewareGroupConfig {
+            name: "api".to_string(),
+            path_prefix: "/api".to_string(),
+            stack: rustpress_core::config::MiddlewareStackConfig {
+                order: Vec::new(),
+                disable: vec!["rate_limit".to_string()],
+            },
+        }];
+        let router = App::new(AppState::for_tests(config)).build_router();
+
+        let bot = "Mozilla/5.0 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)";
+        for _ in 0..3 {
+            let response = router.clone().oneshot(api_request(bot)).await.unwrap();
+            assert_ne!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        }
+
+        // Routes outside the group keep the default stack
+        let page = |uri: &str| {
+            Request::builder()
+                .uri(uri)
+                .header("user-agent", bot)
+                .header("x-forwarded-for", "203.0.113.7")
+                .body(Body::empty())
+                .unwrap()
+        };
+        router.clone().oneshot(page("/missing-1")).await.unwrap();
+        let limited = router.oneshot(page("/missing-2")).await.unwrap();
+        assert_eq!(limited.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
 
     #[test]
     fn test_server_builder() {
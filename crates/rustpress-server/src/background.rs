@@ -6,14 +6,34 @@
 use std::sync::Arc;
 use tracing::{error, info};
 
+use rustpress_events::EventBus;
+
+use crate::services::read_only::CACHE_QUEUE;
+use crate::services::webhooks::WEBHOOK_QUEUE;
+use crate::services::{
+    CacheWarmerService, DeliverWebhookHandler, DispatchSocialSharesHandler,
+    DispatchSocialSharesJob, EvaluateWebVitalsBudgetsHandler, EvaluateWebVitalsBudgetsJob,
+    GeoIpService, InboundEmailService, IndexingService, PollInboundEmailHandler,
+    PollInboundEmailJob, SocialService, SubmitIndexingHandler, SubmitIndexingJob,
+    UpdateGeoIpDatabaseHandler, UpdateGeoIpDatabaseJob, UsageService, WarmPageCacheHandler,
+    WebVitalsService, WebhookService,
+};
 use rustpress_jobs::{
-    CleanThemePreviewsHandler, CleanThemePreviewsJob, JobQueue, PublishScheduledPostsHandler,
-    PublishScheduledPostsJob, Schedule, Scheduler, Worker,
+    CleanThemePreviewsHandler, CleanThemePreviewsJob, EnforcePostEmbargoesHandler,
+    EnforcePostEmbargoesJob, EvaluateJobSlasHandler, EvaluateJobSlasJob, ExecutionAudit, JobQueue,
+    PauseSwitch, PgScheduleStore, PublishScheduledPostsHandler, PublishScheduledPostsJob, Schedule,
+    Scheduler, SlaMonitor, UnpublishExpiredPostsHandler, UnpublishExpiredPostsJob, Worker,
+    WorkerConfig,
 };
 
 /// Initialize and start the job scheduler with periodic tasks
-pub fn init_scheduler(job_queue: Arc<JobQueue>) -> Arc<Scheduler> {
-    let scheduler = Arc::new(Scheduler::new(job_queue.clone()));
+///
+/// Site owners' cron schedules are kept in the `job_schedules` table; load
+/// them with [`Scheduler::load_stored`].
+pub fn init_scheduler(job_queue: Arc<JobQueue>, pool: sqlx::PgPool) -> Arc<Scheduler> {
+    let scheduler = Arc::new(
+        Scheduler::new(job_queue.clone()).with_store(Arc::new(PgScheduleStore::new(pool))),
+    );
 
     // Schedule: Publish scheduled posts every minute
     scheduler.schedule_job(
@@ -22,6 +42,41 @@ pub fn init_scheduler(job_queue: Arc<JobQueue>) -> Arc<Scheduler> {
         PublishScheduledPostsJob { site_id: None },
     );
 
+    // Schedule: Take down expired posts every minute
+    scheduler.schedule_job(
+        "unpublish_expired_posts",
+        Schedule::every_minute(),
+        UnpublishExpiredPostsJob { site_id: None },
+    );
+
+    // Schedule: Withhold posts whose embargo has started every minute
+    scheduler.schedule_job(
+        "enforce_post_embargoes",
+        Schedule::every_minute(),
+        EnforcePostEmbargoesJob { site_id: None },
+    );
+
+    // Schedule: Send due social shares every minute
+    scheduler.schedule_job(
+        "dispatch_social_shares",
+        Schedule::every_minute(),
+        DispatchSocialSharesJob::default(),
+    );
+
+    // Schedule: Submit queued URLs to search engines every minute
+    scheduler.schedule_job(
+        "submit_indexing",
+        Schedule::every_minute(),
+        SubmitIndexingJob::default(),
+    );
+
+    // Schedule: Fetch new mail from IMAP mailboxes every minute
+    scheduler.schedule_job(
+        "poll_inbound_email",
+        Schedule::every_minute(),
+        PollInboundEmailJob::default(),
+    );
+
     // Schedule: Clean expired theme previews every hour
     scheduler.schedule_job(
         "clean_theme_previews",
@@ -29,20 +84,94 @@ pub fn init_scheduler(job_queue: Arc<JobQueue>) -> Arc<Scheduler> {
         CleanThemePreviewsJob { site_id: None },
     );
 
+    // Schedule: Check Core Web Vitals against performance budgets every hour
+    scheduler.schedule_job(
+        "evaluate_web_vitals_budgets",
+        Schedule::hourly(),
+        EvaluateWebVitalsBudgetsJob::default(),
+    );
+
+    // Schedule: Refresh GeoIP databases once they are older than the
+    // configured interval (checked daily)
+    scheduler.schedule_job(
+        "update_geoip_database",
+        Schedule::daily_at(4),
+        UpdateGeoIpDatabaseJob::default(),
+    );
+
+    // Schedule: Check job SLAs against the last hour of executions
+    scheduler.schedule_job(
+        "evaluate_job_slas",
+        Schedule::every_five_minutes(),
+        EvaluateJobSlasJob::default(),
+    );
+
     info!("Job scheduler initialized with periodic tasks:");
     info!("  - publish_scheduled_posts: every minute");
+    info!("  - unpublish_expired_posts: every minute");
+    info!("  - enforce_post_embargoes: every minute");
+    info!("  - dispatch_social_shares: every minute");
+    info!("  - submit_indexing: every minute");
+    info!("  - poll_inbound_email: every minute");
     info!("  - clean_theme_previews: hourly");
+    info!("  - evaluate_web_vitals_budgets: hourly");
+    info!("  - update_geoip_database: daily");
+    info!("  - evaluate_job_slas: every five minutes");
 
     scheduler
 }
 
 /// Start the background worker for processing jobs
-pub fn start_worker(job_queue: Arc<JobQueue>, pool: sqlx::PgPool) {
-    let worker = Worker::new(job_queue);
+///
+/// Finished jobs are announced on the event bus as `job.completed` or
+/// `job.failed`, and every attempt is recorded in the execution audit that
+/// job SLAs are checked against. While `pause` is set (read-only mode) only cache refresh
+/// jobs are processed. The time jobs of network sites run counts against
+/// their job time quota.
+#[allow(clippy::too_many_arguments)]
+pub fn start_worker(
+    job_queue: Arc<JobQueue>,
+    pool: sqlx::PgPool,
+    geoip: Arc<GeoIpService>,
+    cache_warmer: Arc<CacheWarmerService>,
+    social: Arc<SocialService>,
+    webhooks: Arc<WebhookService>,
+    indexing: Arc<IndexingService>,
+    web_vitals: Arc<WebVitalsService>,
+    inbound_email: Arc<InboundEmailService>,
+    events: Arc<EventBus>,
+    pause: PauseSwitch,
+    usage: Arc<UsageService>,
+) {
+    let config = WorkerConfig {
+        queues: vec![
+            "default".to_string(),
+            CACHE_QUEUE.to_string(),
+            WEBHOOK_QUEUE.to_string(),
+        ],
+        unpaused_queues: vec![CACHE_QUEUE.to_string()],
+        ..Default::default()
+    };
+    let sla_monitor = Arc::new(SlaMonitor::new(pool.clone()).with_events(events.clone()));
+    let worker = Worker::with_config(job_queue, config)
+        .with_events(events)
+        .with_audit(Arc::new(ExecutionAudit::new(pool.clone())))
+        .with_pause(pause)
+        .with_meter(usage);
 
     // Register job handlers
     worker.register(PublishScheduledPostsHandler::new(pool.clone()));
+    worker.register(UnpublishExpiredPostsHandler::new(pool.clone()));
+    worker.register(EnforcePostEmbargoesHandler::new(pool.clone()));
     worker.register(CleanThemePreviewsHandler::new(pool.clone()));
+    worker.register(UpdateGeoIpDatabaseHandler::new(geoip));
+    worker.register(WarmPageCacheHandler::new(cache_warmer));
+    worker.register(DispatchSocialSharesHandler::new(social));
+    worker.register(DeliverWebhookHandler::new(webhooks));
+    worker.register(SubmitIndexingHandler::new(indexing));
+    worker.register(EvaluateWebVitalsBudgetsHandler::new(web_vitals));
+    worker.register(PollInboundEmailHandler::new(inbound_email));
+    worker.register(EvaluateJobSlasHandler::new(sla_monitor));
 
     // Spawn worker in background
     tokio::spawn(async move {
@@ -64,14 +193,45 @@ pub fn start_scheduler(scheduler: Arc<Scheduler>) {
 }
 
 /// Initialize all background tasks (scheduler + worker)
-pub async fn init_background_tasks(job_queue: JobQueue, pool: sqlx::PgPool) -> Arc<Scheduler> {
+#[allow(clippy::too_many_arguments)]
+pub async fn init_background_tasks(
+    job_queue: JobQueue,
+    pool: sqlx::PgPool,
+    geoip: Arc<GeoIpService>,
+    cache_warmer: Arc<CacheWarmerService>,
+    social: Arc<SocialService>,
+    webhooks: Arc<WebhookService>,
+    indexing: Arc<IndexingService>,
+    web_vitals: Arc<WebVitalsService>,
+    inbound_email: Arc<InboundEmailService>,
+    events: Arc<EventBus>,
+    pause: PauseSwitch,
+    usage: Arc<UsageService>,
+) -> Arc<Scheduler> {
     let job_queue_arc = Arc::new(job_queue);
 
     // Initialize and start worker
-    start_worker(job_queue_arc.clone(), pool);
+    start_worker(
+        job_queue_arc.clone(),
+        pool.clone(),
+        geoip,
+        cache_warmer,
+        social,
+        webhooks,
+        indexing,
+        web_vitals,
+        inbound_email,
+        events,
+        pause,
+        usage,
+    );
 
     // Initialize scheduler
-    let scheduler = init_scheduler(job_queue_arc);
+    let scheduler = init_scheduler(job_queue_arc, pool);
+    match scheduler.load_stored().await {
+        Ok(count) => info!("  - {} stored schedule(s)", count),
+        Err(e) => error!("Failed to load stored schedules: {}", e),
+    }
 
     // Start scheduler loop
     start_scheduler(scheduler.clone());
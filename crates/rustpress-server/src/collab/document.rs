@@ -0,0 +1,805 @@
+//! Shared document model and merging of concurrent changes.
+//!
+//! A post is edited as a list of top-level blocks addressed by client ID.
+//! Block operations name the blocks they touch rather than their position,
+//! and removed blocks stay behind as tombstones, so inserts and moves
+//! anchored to a block someone else just removed still land in the right
+//! place and no block operation ever conflicts with another: an edit to a
+//! removed block is dropped, and attributes are last-writer-wins per key.
+//!
+//! Text inside a block is merged by operational transformation. The server
+//! orders changes: each change names the version it was made against, and
+//! its text operations are transformed past everything applied since before
+//! being applied and sent to every editor. Offsets count Unicode code
+//! points.
+
+use std::collections::{HashSet, VecDeque};
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Changes kept for transforming late operations; editors further behind
+/// must reload the document
+pub const HISTORY_LIMIT: usize = 1_000;
+
+/// Operations accepted in one change
+pub const MAX_OPERATIONS: usize = 500;
+
+/// Largest document, in bytes of serialized content
+pub const MAX_DOCUMENT_BYTES: usize = 4 * 1024 * 1024;
+
+/// Most blocks, removed ones included, a document may hold
+pub const MAX_BLOCKS: usize = 10_000;
+
+/// Name given to content outside block markup
+pub const FREEFORM_BLOCK: &str = "core/freeform";
+
+static BLOCK_NAME_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[a-z][a-z0-9_-]*(/[a-z][a-z0-9_-]*)?$").unwrap());
+
+/// Block delimiter comments: `<!-- wp:name {attrs} -->`, `<!-- /wp:name -->`
+/// and the self-closing `<!-- wp:name /-->`
+static DELIMITER_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?s)<!--\s+(/)?wp:([a-z][a-z0-9_-]*(?:/[a-z][a-z0-9_-]*)?)\s+(?:(\{.*?\})\s+)?(/)?-->",
+    )
+    .unwrap()
+});
+
+/// A top-level block of a document
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DocumentBlock {
+    /// Client ID the editor addresses the block by
+    pub id: String,
+    /// Block name, e.g. `core/paragraph`
+    pub name: String,
+    #[serde(default)]
+    pub attributes: Map<String, Value>,
+    /// Inner HTML, including the markup of nested blocks
+    #[serde(default)]
+    pub content: String,
+}
+
+/// A change to a document
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Operation {
+    SetTitle {
+        title: String,
+    },
+    /// Insert a block after another, or first when `after` is unset
+    InsertBlock {
+        block: DocumentBlock,
+        after: Option<String>,
+    },
+    RemoveBlock {
+        id: String,
+    },
+    MoveBlock {
+        id: String,
+        after: Option<String>,
+    },
+    /// Set attributes; null values remove them
+    SetAttributes {
+        id: String,
+        attributes: Map<String, Value>,
+    },
+    InsertText {
+        id: String,
+        offset: usize,
+        text: String,
+    },
+    DeleteText {
+        id: String,
+        offset: usize,
+        length: usize,
+    },
+}
+
+impl Operation {
+    /// Block whose text the operation edits
+    fn text_block(&self) -> Option<&str> {
+        match self {
+            Self::InsertText { id, .. } | Self::DeleteText { id, .. } => Some(id),
+            _ => None,
+        }
+    }
+}
+
+/// Why a change was refused
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum DocumentError {
+    /// The change is based on a version no longer in the history
+    #[error("Version {0} is too old; reload the document")]
+    Stale(u64),
+    #[error("Version {0} does not exist yet")]
+    UnknownVersion(u64),
+    #[error("{0}")]
+    Invalid(String),
+}
+
+fn invalid(message: impl Into<String>) -> DocumentError {
+    DocumentError::Invalid(message.into())
+}
+
+/// Where an editor's caret is
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Caret {
+    pub block_id: String,
+    pub offset: usize,
+}
+
+impl Caret {
+    /// Keep the caret on the same text after an operation
+    pub fn transform(&mut self, op: &Operation) {
+        match op {
+            Operation::InsertText { id, offset, text }
+                if *id == self.block_id && *offset <= self.offset =>
+            {
+                self.offset += text.chars().count();
+            }
+            Operation::DeleteText { id, offset, length }
+                if *id == self.block_id && self.offset > *offset =>
+            {
+                self.offset -= (*length).min(self.offset - offset);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// A document as sent to an editor joining it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DocumentSnapshot {
+    pub version: u64,
+    pub title: String,
+    pub blocks: Vec<DocumentBlock>,
+}
+
+#[derive(Debug, Clone)]
+struct Slot {
+    block: DocumentBlock,
+    removed: bool,
+}
+
+/// Editable state of a document
+#[derive(Debug, Clone)]
+struct State {
+    title: String,
+    slots: Vec<Slot>,
+}
+
+impl State {
+    fn position(&self, id: &str) -> Option<usize> {
+        self.slots.iter().position(|slot| slot.block.id == id)
+    }
+
+    /// A live block, or None when it was removed
+    fn live_block(&mut self, id: &str) -> Result<Option<&mut DocumentBlock>, DocumentError> {
+        let slot = self
+            .slots
+            .iter_mut()
+            .find(|slot| slot.block.id == id)
+            .ok_or_else(|| invalid(format!("There is no block '{}'", id)))?;
+        Ok((!slot.removed).then_some(&mut slot.block))
+    }
+
+    /// Index a block goes to when placed after `after`
+    fn insertion_point(&self, after: Option<&str>) -> Result<usize, DocumentError> {
+        match after {
+            None => Ok(0),
+            Some(after) => self
+                .position(after)
+                .map(|i| i + 1)
+                .ok_or_else(|| invalid(format!("There is no block '{}'", after))),
+        }
+    }
+
+    /// Apply one operation; returns false when it was dropped because its
+    /// block has been removed
+    fn apply(&mut self, op: &Operation) -> Result<bool, DocumentError> {
+        match op {
+            Operation::SetTitle { title } => self.title = title.clone(),
+            Operation::InsertBlock { block, after } => {
+                if !BLOCK_NAME_RE.is_match(&block.name) {
+                    return Err(invalid(format!("'{}' is not a block name", block.name)));
+                }
+                if block.id.is_empty() || block.id.len() > 64 {
+                    return Err(invalid("Block IDs must be 1 to 64 characters"));
+                }
+                if self.position(&block.id).is_some() {
+                    return Err(invalid(format!("Block '{}' already exists", block.id)));
+                }
+                if self.slots.len() >= MAX_BLOCKS {
+                    return Err(invalid("The document has too many blocks"));
+                }
+                let index = self.insertion_point(after.as_deref())?;
+                self.slots.insert(
+                    index,
+                    Slot {
+                        block: block.clone(),
+                        removed: false,
+                    },
+                );
+            }
+            Operation::RemoveBlock { id } => {
+                let index = self
+                    .position(id)
+                    .ok_or_else(|| invalid(format!("There is no block '{}'", id)))?;
+                if self.slots[index].removed {
+                    return Ok(false);
+                }
+                self.slots[index].removed = true;
+            }
+            Operation::MoveBlock { id, after } => {
+                if after.as_deref() == Some(id.as_str()) {
+                    return Ok(false);
+                }
+                let from = self
+                    .position(id)
+                    .ok_or_else(|| invalid(format!("There is no block '{}'", id)))?;
+                self.insertion_point(after.as_deref())?;
+                if self.slots[from].removed {
+                    return Ok(false);
+                }
+                let slot = self.slots.remove(from);
+                let to = self.insertion_point(after.as_deref())?;
+                self.slots.insert(to, slot);
+            }
+            Operation::SetAttributes { id, attributes } => {
+                let Some(block) = self.live_block(id)? else {
+                    return Ok(false);
+                };
+                for (key, value) in attributes {
+                    if value.is_null() {
+                        block.attributes.remove(key);
+                    } else {
+                        block.attributes.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+            Operation::InsertText { id, offset, text } => {
+                let Some(block) = self.live_block(id)? else {
+                    return Ok(false);
+                };
+                let at = byte_index(&block.content, *offset).ok_or_else(|| {
+                    invalid(format!("Offset {} is past the end of '{}'", offset, id))
+                })?;
+                block.content.insert_str(at, text);
+            }
+            Operation::DeleteText { id, offset, length } => {
+                let Some(block) = self.live_block(id)? else {
+                    return Ok(false);
+                };
+                let range = byte_index(&block.content, *offset)
+                    .zip(byte_index(&block.content, offset + length))
+                    .ok_or_else(|| invalid(format!("Deletion runs past the end of '{}'", id)))?;
+                block.content.replace_range(range.0..range.1, "");
+            }
+        }
+        Ok(true)
+    }
+
+    fn size(&self) -> usize {
+        self.title.len()
+            + self
+                .slots
+                .iter()
+                .filter(|slot| !slot.removed)
+                .map(|slot| slot.block.content.len() + slot.block.name.len() + 64)
+                .sum::<usize>()
+    }
+}
+
+/// Byte index of a code point offset; the end of the string is valid
+fn byte_index(text: &str, offset: usize) -> Option<usize> {
+    text.char_indices()
+        .map(|(i, _)| i)
+        .chain(std::iter::once(text.len()))
+        .nth(offset)
+}
+
+/// Transform `op` so it applies after `against`, both having been made
+/// against the same document. `wins_ties` puts `op`'s text first when both
+/// insert at the same place.
+pub fn transform(op: &Operation, against: &Operation, wins_ties: bool) -> Vec<Operation> {
+    use Operation::{DeleteText, InsertText};
+
+    if op.text_block().is_none() || op.text_block() != against.text_block() {
+        return vec![op.clone()];
+    }
+
+    match (op, against) {
+        (
+            InsertText { id, offset, text },
+            InsertText {
+                offset: other,
+                text: inserted,
+                ..
+            },
+        ) => {
+            let offset = if *other < *offset || (*other == *offset && !wins_ties) {
+                offset + inserted.chars().count()
+            } else {
+                *offset
+            };
+            vec![InsertText {
+                id: id.clone(),
+                offset,
+                text: text.clone(),
+            }]
+        }
+        (
+            InsertText { id, offset, text },
+            DeleteText {
+                offset: start,
+                length,
+                ..
+            },
+        ) => {
+            let offset = if *offset <= *start {
+                *offset
+            } else if *offset >= start + length {
+                offset - length
+            } else {
+                *start
+            };
+            vec![InsertText {
+                id: id.clone(),
+                offset,
+                text: text.clone(),
+            }]
+        }
+        (
+            DeleteText { id, offset, length },
+            InsertText {
+                offset: at,
+                text: inserted,
+                ..
+            },
+        ) => {
+            let inserted = inserted.chars().count();
+            if *at <= *offset {
+                vec![DeleteText {
+                    id: id.clone(),
+                    offset: offset + inserted,
+                    length: *length,
+                }]
+            } else if *at >= offset + length {
+                vec![op.clone()]
+            } else {
+                // Delete around the inserted text, keeping it
+                vec![
+                    DeleteText {
+                        id: id.clone(),
+                        offset: *offset,
+                        length: at - offset,
+                    },
+                    DeleteText {
+                        id: id.clone(),
+                        offset: offset + inserted,
+                        length: offset + length - at,
+                    },
+                ]
+            }
+        }
+        (
+            DeleteText { id, offset, length },
+            DeleteText {
+                offset: start,
+                length: removed,
+                ..
+            },
+        ) => {
+            let (end, removed_end) = (offset + length, start + removed);
+            let (offset, length) = if end <= *start {
+                (*offset, *length)
+            } else if *offset >= removed_end {
+                (offset - removed, *length)
+            } else {
+                let overlap = end.min(removed_end) - (*offset).max(*start);
+                ((*offset).min(*start), length - overlap)
+            };
+            if length == 0 {
+                return Vec::new();
+            }
+            vec![DeleteText {
+                id: id.clone(),
+                offset,
+                length,
+            }]
+        }
+        _ => vec![op.clone()],
+    }
+}
+
+/// Transform two sequences made against the same document past each
+/// other: returns `ops` as it applies after `against`, and `against` as it
+/// applies after `ops`. `against` wins ties.
+fn transform_sequences(
+    ops: &[Operation],
+    against: &[Operation],
+) -> (Vec<Operation>, Vec<Operation>) {
+    match (ops, against) {
+        ([], _) => (Vec::new(), against.to_vec()),
+        (_, []) => (ops.to_vec(), Vec::new()),
+        ([op], [other]) => (transform(op, other, false), transform(other, op, true)),
+        ([_], [first, rest @ ..]) => {
+            let (ops, mut against) = transform_sequences(ops, std::slice::from_ref(first));
+            let (ops, mut rest) = transform_sequences(&ops, rest);
+            against.append(&mut rest);
+            (ops, against)
+        }
+        ([first, rest @ ..], _) => {
+            let (first, against) = transform_sequences(std::slice::from_ref(first), against);
+            let (mut rest, against) = transform_sequences(rest, &against);
+            let mut ops = first;
+            ops.append(&mut rest);
+            (ops, against)
+        }
+    }
+}
+
+/// A document being edited, with the history needed to merge late changes
+#[derive(Debug, Clone)]
+pub struct Document {
+    state: State,
+    version: u64,
+    /// Operations applied, by the version they produced
+    history: VecDeque<(u64, Vec<Operation>)>,
+}
+
+impl Document {
+    /// Split post content into top-level blocks; content outside block
+    /// markup becomes freeform blocks
+    pub fn from_content(title: &str, content: &str) -> Self {
+        let mut blocks = Vec::new();
+        let freeform = |blocks: &mut Vec<DocumentBlock>, text: &str| {
+            let text = text.trim();
+            if !text.is_empty() {
+                blocks.push(new_block(FREEFORM_BLOCK, Map::new(), text));
+            }
+        };
+
+        // Open top-level block: name, attributes and where its content starts
+        let mut open: Option<(String, Map<String, Value>, usize)> = None;
+        let mut depth = 0usize;
+        let mut last_end = 0;
+        for caps in DELIMITER_RE.captures_iter(content) {
+            let token = caps.get(0).unwrap();
+            let closer = caps.get(1).is_some();
+            let void = caps.get(4).is_some();
+
+            if depth == 0 {
+                if closer {
+                    continue;
+                }
+                freeform(&mut blocks, &content[last_end..token.start()]);
+                let name = qualified_name(&caps[2]);
+                let attributes = caps
+                    .get(3)
+                    .and_then(|attrs| serde_json::from_str(attrs.as_str()).ok())
+                    .unwrap_or_default();
+                if void {
+                    blocks.push(new_block(&name, attributes, ""));
+                } else {
+                    open = Some((name, attributes, token.end()));
+                    depth = 1;
+                }
+                last_end = token.end();
+            } else if closer {
+                depth -= 1;
+                if depth == 0 {
+                    if let Some((name, attributes, start)) = open.take() {
+                        blocks.push(new_block(
+                            &name,
+                            attributes,
+                            content[start..token.start()].trim(),
+                        ));
+                    }
+                    last_end = token.end();
+                }
+            } else if !void {
+                depth += 1;
+            }
+        }
+        match open {
+            // Unclosed block: it runs to the end
+            Some((name, attributes, start)) => {
+                blocks.push(new_block(&name, attributes, content[start..].trim()))
+            }
+            None => freeform(&mut blocks, &content[last_end..]),
+        }
+
+        Self {
+            state: State {
+                title: title.to_string(),
+                slots: blocks
+                    .into_iter()
+                    .map(|block| Slot {
+                        block,
+                        removed: false,
+                    })
+                    .collect(),
+            },
+            version: 0,
+            history: VecDeque::new(),
+        }
+    }
+
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    pub fn title(&self) -> &str {
+        &self.state.title
+    }
+
+    /// Blocks in order, removed ones left out
+    pub fn blocks(&self) -> impl Iterator<Item = &DocumentBlock> {
+        self.state
+            .slots
+            .iter()
+            .filter(|slot| !slot.removed)
+            .map(|slot| &slot.block)
+    }
+
+    pub fn snapshot(&self) -> DocumentSnapshot {
+        DocumentSnapshot {
+            version: self.version,
+            title: self.state.title.clone(),
+            blocks: self.blocks().cloned().collect(),
+        }
+    }
+
+    /// Serialize the blocks back into post content
+    pub fn to_content(&self) -> String {
+        self.blocks()
+            .map(|block| {
+                if block.name == FREEFORM_BLOCK {
+                    return block.content.clone();
+                }
+                let name = block.name.strip_prefix("core/").unwrap_or(&block.name);
+                let attributes = if block.attributes.is_empty() {
+                    String::new()
+                } else {
+                    format!(" {}", Value::Object(block.attributes.clone()))
+                };
+                if block.content.is_empty() {
+                    format!("<!-- wp:{}{} /-->", name, attributes)
+                } else {
+                    format!(
+                        "<!-- wp:{}{} -->\n{}\n<!-- /wp:{} -->",
+                        name, attributes, block.content, name
+                    )
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    /// Apply a change made against `base_version`. Returns the operations
+    /// as applied, for every editor to apply in turn; the change is
+    /// refused as a whole if any of it is invalid.
+    pub fn apply(
+        &mut self,
+        base_version: u64,
+        ops: Vec<Operation>,
+    ) -> Result<Vec<Operation>, DocumentError> {
+        if base_version > self.version {
+            return Err(DocumentError::UnknownVersion(base_version));
+        }
+        if ops.len() > MAX_OPERATIONS {
+            return Err(invalid(format!(
+                "Changes can have at most {} operations",
+                MAX_OPERATIONS
+            )));
+        }
+        let oldest = self.history.front().map_or(self.version, |(v, _)| v - 1);
+        if base_version < oldest {
+            return Err(DocumentError::Stale(base_version));
+        }
+
+        let concurrent: Vec<Operation> = self
+            .history
+            .iter()
+            .filter(|(version, _)| *version > base_version)
+            .flat_map(|(_, ops)| ops.iter().cloned())
+            .collect();
+        let (ops, _) = transform_sequences(&ops, &concurrent);
+
+        let mut state = self.state.clone();
+        let mut applied = Vec::with_capacity(ops.len());
+        for op in ops {
+            if state.apply(&op)? {
+                applied.push(op);
+            }
+        }
+        if state.size() > MAX_DOCUMENT_BYTES {
+            return Err(invalid("The document is too large"));
+        }
+
+        self.state = state;
+        if !applied.is_empty() {
+            self.version += 1;
+            self.history.push_back((self.version, applied.clone()));
+            if self.history.len() > HISTORY_LIMIT {
+                self.history.pop_front();
+            }
+        }
+        Ok(applied)
+    }
+
+    /// IDs of every block, removed ones included
+    pub fn block_ids(&self) -> HashSet<&str> {
+        self.state
+            .slots
+            .iter()
+            .map(|slot| slot.block.id.as_str())
+            .collect()
+    }
+}
+
+fn qualified_name(name: &str) -> String {
+    if name.contains('/') {
+        name.to_string()
+    } else {
+        format!("core/{}", name)
+    }
+}
+
+fn new_block(name: &str, attributes: Map<String, Value>, content: &str) -> DocumentBlock {
+    DocumentBlock {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: name.to_string(),
+        attributes,
+        content: content.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn insert(id: &str, offset: usize, text: &str) -> Operation {
+        Operation::InsertText {
+            id: id.to_string(),
+            offset,
+            text: text.to_string(),
+        }
+    }
+
+    fn delete(id: &str, offset: usize, length: usize) -> Operation {
+        Operation::DeleteText {
+            id: id.to_string(),
+            offset,
+            length,
+        }
+    }
+
+    #[test]
+    fn test_content_round_trip() {
+        let content = "Intro text\n\n<!-- wp:heading {\"level\":2} -->\n<h2>Title</h2>\n<!-- /wp:heading -->\n\n<!-- wp:columns -->\n<div><!-- wp:column -->\n<p>a</p>\n<!-- /wp:column --></div>\n<!-- /wp:columns -->\n\n<!-- wp:separator /-->";
+        let document = Document::from_content("Post", content);
+        let names: Vec<_> = document.blocks().map(|b| b.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "core/freeform",
+                "core/heading",
+                "core/columns",
+                "core/separator"
+            ]
+        );
+        assert_eq!(document.blocks().nth(1).unwrap().attributes["level"], 2);
+        assert!(document
+            .blocks()
+            .nth(2)
+            .unwrap()
+            .content
+            .contains("<!-- /wp:column -->"));
+        assert_eq!(document.to_content(), content);
+
+        let classic = Document::from_content("Post", "<p>Classic</p>");
+        assert_eq!(classic.to_content(), "<p>Classic</p>");
+    }
+
+    #[test]
+    fn test_concurrent_text_edits_converge() {
+        let mut document = Document::from_content(
+            "Post",
+            "<!-- wp:paragraph -->\nHello world\n<!-- /wp:paragraph -->",
+        );
+        let id = document.blocks().next().unwrap().id.clone();
+
+        // Two editors at version 0: one inserts, the other deletes "world"
+        document.apply(0, vec![insert(&id, 5, ",")]).unwrap();
+        let applied = document.apply(0, vec![delete(&id, 6, 5)]).unwrap();
+        assert_eq!(applied, vec![delete(&id, 7, 5)]);
+        assert_eq!(document.blocks().next().unwrap().content, "Hello, ");
+
+        // A deletion spanning someone's insertion keeps the inserted text
+        let applied = document.apply(0, vec![delete(&id, 0, 6)]).unwrap();
+        assert_eq!(applied, vec![delete(&id, 0, 5), delete(&id, 1, 1)]);
+        assert_eq!(document.blocks().next().unwrap().content, ",");
+
+        // Multi-operation changes are transformed in sequence
+        let applied = document
+            .apply(1, vec![insert(&id, 0, "A"), insert(&id, 1, "B")])
+            .unwrap();
+        assert_eq!(applied, vec![insert(&id, 0, "A"), insert(&id, 1, "B")]);
+        assert_eq!(document.blocks().next().unwrap().content, "AB,");
+        assert_eq!(document.version(), 4);
+
+        assert_eq!(
+            document.apply(9, vec![insert(&id, 0, "x")]),
+            Err(DocumentError::UnknownVersion(9))
+        );
+        assert!(document.apply(4, vec![insert(&id, 99, "x")]).is_err());
+        assert_eq!(document.version(), 4);
+    }
+
+    #[test]
+    fn test_block_operations_never_conflict() {
+        let mut document = Document::from_content(
+            "Post",
+            "<!-- wp:paragraph -->\na\n<!-- /wp:paragraph -->\n\n<!-- wp:paragraph -->\nb\n<!-- /wp:paragraph -->",
+        );
+        let ids: Vec<String> = document.blocks().map(|b| b.id.clone()).collect();
+        let block = |id: &str| DocumentBlock {
+            id: id.to_string(),
+            name: "core/paragraph".to_string(),
+            attributes: Map::new(),
+            content: id.to_string(),
+        };
+
+        // One editor removes the first block while others insert after it,
+        // type into it and move it
+        document
+            .apply(0, vec![Operation::RemoveBlock { id: ids[0].clone() }])
+            .unwrap();
+        document
+            .apply(
+                0,
+                vec![Operation::InsertBlock {
+                    block: block("c"),
+                    after: Some(ids[0].clone()),
+                }],
+            )
+            .unwrap();
+        let dropped = document
+            .apply(
+                0,
+                vec![
+                    insert(&ids[0], 0, "x"),
+                    Operation::MoveBlock {
+                        id: ids[0].clone(),
+                        after: Some(ids[1].clone()),
+                    },
+                ],
+            )
+            .unwrap();
+        assert!(dropped.is_empty());
+        document
+            .apply(
+                2,
+                vec![Operation::MoveBlock {
+                    id: ids[1].clone(),
+                    after: None,
+                }],
+            )
+            .unwrap();
+
+        let contents: Vec<_> = document.blocks().map(|b| b.content.as_str()).collect();
+        assert_eq!(contents, ["b", "c"]);
+        assert_eq!(document.version(), 3);
+
+        let duplicate = Operation::InsertBlock {
+            block: block("c"),
+            after: None,
+        };
+        assert!(document.apply(3, vec![duplicate]).is_err());
+    }
+}
@@ -0,0 +1,288 @@
+//! Collaborative editing socket handler.
+//!
+//! As on the live notification socket, the token is sent in the first
+//! frame:
+//!
+//! ```text
+//! -> {"type":"auth","token":"<access token>"}
+//! <- {"type":"ready","connection_id":"...","document":{...},"collaborators":[...],"autosave":null}
+//! -> {"type":"change","change_id":"...","base_version":4,"ops":[{"op":"insert_text",...}]}
+//! <- {"type":"applied","version":5,"change_id":"...","user_id":"...","ops":[...]}
+//! -> {"type":"presence","caret":{"block_id":"...","offset":12},"selection":null}
+//! ```
+
+use std::borrow::Cow;
+use std::time::Duration;
+
+use axum::{
+    extract::{
+        ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade},
+        State,
+    },
+    response::IntoResponse,
+};
+use futures::{stream::SplitStream, SinkExt, StreamExt};
+use rustpress_core::error::Error;
+use rustpress_core::tenant::{self, current_site, with_site};
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use super::document::DocumentError;
+use super::manager::CONNECTION_BUFFER;
+use super::message::{ClientMessage, ServerMessage};
+use crate::extract::{require_permission, AuthUser, PathId};
+use crate::routes::check_section_access;
+use crate::state::AppState;
+
+/// Time allowed between upgrade and the `auth` message
+const AUTH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Interval between server pings on an idle connection
+const PING_INTERVAL: Duration = Duration::from_secs(30);
+
+const CLOSE_UNAUTHORIZED: u16 = 4401;
+const CLOSE_FORBIDDEN: u16 = 4403;
+const CLOSE_NOT_FOUND: u16 = 4404;
+const CLOSE_AUTH_TIMEOUT: u16 = 4408;
+const CLOSE_DROPPED: u16 = 4409;
+
+/// WebSocket upgrade handler for editing a post together
+pub async fn collaborate_handler(
+    ws: WebSocketUpgrade,
+    PathId(post_id): PathId,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let site = current_site();
+    ws.on_upgrade(move |socket| with_site(site, handle_socket(socket, post_id, state)))
+}
+
+/// Identity established by the auth handshake
+struct Handshake {
+    user_id: Uuid,
+    name: String,
+}
+
+async fn handle_socket(socket: WebSocket, post_id: Uuid, state: AppState) {
+    let (mut sender, mut receiver) = socket.split();
+
+    let handshake = match tokio::time::timeout(
+        AUTH_TIMEOUT,
+        authenticate(&mut receiver, &state, post_id),
+    )
+    .await
+    {
+        Ok(Ok(handshake)) => handshake,
+        Ok(Err((code, reason))) => {
+            let _ = sender.send(close(code, reason)).await;
+            return;
+        }
+        Err(_) => {
+            let _ = sender
+                .send(close(CLOSE_AUTH_TIMEOUT, "Authentication timed out"))
+                .await;
+            return;
+        }
+    };
+
+    let connection_id = Uuid::new_v4();
+    let manager = state.collab.clone();
+
+    // Document messages come from the session, which holds the only sender
+    // and drops it when the editor falls behind; replies to this editor
+    // alone go through a second channel
+    let (doc_tx, mut doc_rx) = mpsc::channel::<ServerMessage>(CONNECTION_BUFFER);
+    let (reply_tx, mut reply_rx) = mpsc::channel::<ServerMessage>(CONNECTION_BUFFER);
+
+    let ready = match manager
+        .join(
+            post_id,
+            connection_id,
+            handshake.user_id,
+            handshake.name,
+            doc_tx,
+        )
+        .await
+    {
+        Ok(ready) => ready,
+        Err(e) => {
+            let (code, reason) = match e {
+                Error::NotFound { .. } => (CLOSE_NOT_FOUND, "Post not found"),
+                Error::Validation(_) => (CLOSE_FORBIDDEN, "Too many people are editing this post"),
+                e => {
+                    warn!(post_id = %post_id, "Failed to join editing session: {}", e);
+                    (CLOSE_UNAUTHORIZED, "Could not join the editing session")
+                }
+            };
+            let _ = sender.send(close(code, reason)).await;
+            return;
+        }
+    };
+    let _ = reply_tx.send(ready).await;
+
+    info!(
+        user = %handshake.user_id,
+        post = %post_id,
+        connection = %connection_id,
+        "Collaborative editor connected"
+    );
+
+    // Outgoing: session messages, replies and periodic pings
+    let mut send_task = tenant::spawn(async move {
+        let mut ping = tokio::time::interval(PING_INTERVAL);
+        ping.tick().await;
+        loop {
+            let message = tokio::select! {
+                message = doc_rx.recv() => match message {
+                    Some(message) => message,
+                    None => {
+                        let _ = sender.send(close(CLOSE_DROPPED, "Fell behind; reconnect")).await;
+                        break;
+                    }
+                },
+                message = reply_rx.recv() => {
+                    let Some(message) = message else { break };
+                    message
+                }
+                _ = ping.tick() => {
+                    if sender.send(Message::Ping(Vec::new())).await.is_err() {
+                        break;
+                    }
+                    continue;
+                }
+            };
+            let Ok(text) = serde_json::to_string(&message) else {
+                continue;
+            };
+            if sender.send(Message::Text(text)).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    // Incoming: changes and presence
+    let recv_manager = manager.clone();
+    let mut recv_task = tenant::spawn(async move {
+        while let Some(Ok(message)) = receiver.next().await {
+            let text = match message {
+                Message::Text(text) => text,
+                Message::Close(_) => break,
+                _ => continue,
+            };
+
+            let reply = match serde_json::from_str::<ClientMessage>(&text) {
+                Ok(ClientMessage::Change {
+                    change_id,
+                    base_version,
+                    ops,
+                }) => match recv_manager
+                    .change(post_id, connection_id, change_id, base_version, ops)
+                    .await
+                {
+                    Ok(()) => None,
+                    Err(e @ (DocumentError::Stale(_) | DocumentError::UnknownVersion(_))) => {
+                        Some(ServerMessage::error("stale", e.to_string()))
+                    }
+                    Err(e) => Some(ServerMessage::error("invalid_change", e.to_string())),
+                },
+                Ok(ClientMessage::Presence { caret, selection }) => {
+                    recv_manager
+                        .presence(post_id, connection_id, caret, selection)
+                        .await;
+                    None
+                }
+                Ok(ClientMessage::Sync) => {
+                    Some(recv_manager.document(post_id).await.unwrap_or_else(|| {
+                        ServerMessage::error("not_joined", "The editing session has ended")
+                    }))
+                }
+                Ok(ClientMessage::Ping) => Some(ServerMessage::Pong),
+                Ok(ClientMessage::Auth { .. }) => Some(ServerMessage::error(
+                    "already_authenticated",
+                    "Connection is already authenticated",
+                )),
+                Err(e) => {
+                    debug!("Invalid collaboration message: {}", e);
+                    Some(ServerMessage::error(
+                        "invalid_message",
+                        "Failed to parse message",
+                    ))
+                }
+            };
+
+            if let Some(reply) = reply {
+                if reply_tx.send(reply).await.is_err() {
+                    break;
+                }
+            }
+        }
+    });
+
+    tokio::select! {
+        _ = &mut send_task => recv_task.abort(),
+        _ = &mut recv_task => send_task.abort(),
+    }
+
+    manager.leave(post_id, connection_id).await;
+    info!(
+        user = %handshake.user_id,
+        post = %post_id,
+        connection = %connection_id,
+        "Collaborative editor disconnected"
+    );
+}
+
+/// Wait for the `auth` message, validate its token and check the user may
+/// edit the post
+async fn authenticate(
+    receiver: &mut SplitStream<WebSocket>,
+    state: &AppState,
+    post_id: Uuid,
+) -> Result<Handshake, (u16, &'static str)> {
+    let token = loop {
+        match receiver.next().await {
+            Some(Ok(Message::Text(text))) => match serde_json::from_str::<ClientMessage>(&text) {
+                Ok(ClientMessage::Auth { token }) => break token,
+                _ => return Err((CLOSE_UNAUTHORIZED, "Expected auth message")),
+            },
+            Some(Ok(Message::Ping(_) | Message::Pong(_))) => continue,
+            _ => return Err((CLOSE_UNAUTHORIZED, "Expected auth message")),
+        }
+    };
+
+    let claims = state.jwt.validate_access_token(&token).map_err(|e| {
+        warn!("Invalid collaboration socket token: {}", e);
+        (CLOSE_UNAUTHORIZED, "Invalid or expired token")
+    })?;
+    let user = AuthUser::from_claims(claims, state)
+        .await
+        .map_err(|_| (CLOSE_UNAUTHORIZED, "Invalid user ID in token"))?;
+
+    require_permission(&user, state, "posts", "edit")
+        .await
+        .map_err(|_| (CLOSE_FORBIDDEN, "Missing permission: posts:edit"))?;
+    check_section_access(state, &user, Some(post_id), None)
+        .await
+        .map_err(|_| (CLOSE_FORBIDDEN, "Post is in a section you cannot edit"))?;
+
+    let name: Option<String> =
+        sqlx::query_scalar("SELECT COALESCE(display_name, username) FROM users WHERE id = $1")
+            .bind(user.id)
+            .fetch_optional(state.db().inner())
+            .await
+            .ok()
+            .flatten();
+    let name = name.ok_or((CLOSE_UNAUTHORIZED, "User not found"))?;
+
+    Ok(Handshake {
+        user_id: user.id,
+        name,
+    })
+}
+
+fn close(code: u16, reason: &'static str) -> Message {
+    Message::Close(Some(CloseFrame {
+        code,
+        reason: Cow::Borrowed(reason),
+    }))
+}
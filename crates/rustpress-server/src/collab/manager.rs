@@ -0,0 +1,564 @@
+//! Editing sessions and autosave snapshots.
+//!
+//! A session exists while anyone has a post open in the editor. It holds
+//! the shared [`Document`], orders the changes made to it and relays them,
+//! along with carets and selections, to every editor in the session.
+//! Sessions live in the memory of the node the editors are connected to,
+//! so a post's editors must reach the same node.
+//!
+//! Documents are written to the `post_autosaves` table every
+//! [`AUTOSAVE_INTERVAL`] while they change and when the last editor leaves;
+//! the post itself only changes when an editor saves it.
+
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use rustpress_core::error::{Error, Result};
+use rustpress_core::tenant::site_filter;
+use serde::Serialize;
+use sqlx::types::Json;
+use sqlx::{FromRow, PgPool};
+use tokio::sync::{mpsc, Mutex, RwLock};
+use tokio::task::JoinHandle;
+use tracing::warn;
+use uuid::Uuid;
+
+use super::document::{Caret, Document, DocumentError, Operation};
+use super::message::{AutosaveNotice, Collaborator, Selection, ServerMessage};
+use crate::websocket::hub::generate_user_color;
+
+/// Document messages buffered per editor; an editor falling further behind
+/// is dropped from the session and must reconnect
+pub const CONNECTION_BUFFER: usize = 512;
+
+/// Editors allowed in one session
+pub const MAX_COLLABORATORS: usize = 20;
+
+/// How often changed documents are autosaved
+pub const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Autosaves kept per post
+const AUTOSAVES_KEPT: i64 = 20;
+
+/// Sender for the document messages of one editor
+pub type CollabSender = mpsc::Sender<ServerMessage>;
+
+/// An editor in a session
+struct Participant {
+    user_id: Uuid,
+    name: String,
+    color: String,
+    caret: Option<Caret>,
+    selection: Option<Selection>,
+    sender: CollabSender,
+}
+
+impl Participant {
+    fn collaborator(&self, connection_id: Uuid) -> Collaborator {
+        Collaborator {
+            connection_id,
+            user_id: self.user_id,
+            name: self.name.clone(),
+            color: self.color.clone(),
+            caret: self.caret.clone(),
+            selection: self.selection.clone(),
+        }
+    }
+}
+
+/// A post open in the editor
+struct Session {
+    document: Document,
+    participants: HashMap<Uuid, Participant>,
+    /// Version last written to an autosave
+    saved_version: u64,
+    /// Users who changed the document since the last autosave
+    contributors: BTreeSet<Uuid>,
+    /// Set once the last editor left and the session was taken down
+    closed: bool,
+}
+
+impl Session {
+    fn collaborators(&self) -> Vec<Collaborator> {
+        self.participants
+            .iter()
+            .map(|(id, participant)| participant.collaborator(*id))
+            .collect()
+    }
+
+    /// Send a message to every editor but `except`. Editors whose buffer
+    /// is full are dropped, which closes their connection.
+    fn broadcast(&mut self, message: ServerMessage, except: Option<Uuid>) {
+        let mut dropped = Vec::new();
+        for (connection_id, participant) in &self.participants {
+            if Some(*connection_id) == except {
+                continue;
+            }
+            if participant.sender.try_send(message.clone()).is_err() {
+                dropped.push(*connection_id);
+            }
+        }
+        for connection_id in dropped {
+            if let Some(participant) = self.participants.remove(&connection_id) {
+                warn!(
+                    connection = %connection_id,
+                    user = %participant.user_id,
+                    "Dropping editor that fell behind"
+                );
+                self.broadcast(
+                    ServerMessage::Left {
+                        connection_id,
+                        user_id: participant.user_id,
+                    },
+                    None,
+                );
+            }
+        }
+    }
+
+    fn send(&mut self, connection_id: Uuid, message: ServerMessage) {
+        let full = self
+            .participants
+            .get(&connection_id)
+            .is_some_and(|p| p.sender.try_send(message).is_err());
+        if full {
+            if let Some(participant) = self.participants.remove(&connection_id) {
+                self.broadcast(
+                    ServerMessage::Left {
+                        connection_id,
+                        user_id: participant.user_id,
+                    },
+                    None,
+                );
+            }
+        }
+    }
+}
+
+/// A snapshot of a collaboratively edited post
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct Autosave {
+    pub id: Uuid,
+    pub post_id: Uuid,
+    /// Session version the snapshot was taken at
+    pub version: i64,
+    pub title: String,
+    pub content: String,
+    /// Users whose changes went into the snapshot
+    pub contributors: Json<Vec<Uuid>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// An autosave without its content, for listing
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct AutosaveSummary {
+    pub id: Uuid,
+    pub post_id: Uuid,
+    pub version: i64,
+    pub title: String,
+    pub contributors: Json<Vec<Uuid>>,
+    /// Content length in characters
+    pub length: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Who is editing a post right now
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionStatus {
+    pub active: bool,
+    pub version: u64,
+    pub collaborators: Vec<Collaborator>,
+}
+
+/// What was captured for an autosave
+struct PendingAutosave {
+    version: u64,
+    title: String,
+    content: String,
+    contributors: Vec<Uuid>,
+}
+
+/// Collaborative editing sessions, one per open post
+pub struct CollabManager {
+    pool: PgPool,
+    sessions: RwLock<HashMap<Uuid, Arc<Mutex<Session>>>>,
+}
+
+impl CollabManager {
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            sessions: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// The session of a post, starting it from the saved post if needed
+    async fn session(&self, post_id: Uuid) -> Result<Arc<Mutex<Session>>> {
+        if let Some(session) = self.sessions.read().await.get(&post_id) {
+            return Ok(session.clone());
+        }
+
+        let (title, content): (String, Option<String>) = sqlx::query_as(&format!(
+            "SELECT title, content FROM posts WHERE id = $1 AND {} AND deleted_at IS NULL",
+            site_filter("site_id")
+        ))
+        .bind(post_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load post", e))?
+        .ok_or_else(|| Error::not_found("Post", post_id.to_string()))?;
+        let session = Session {
+            document: Document::from_content(&title, content.as_deref().unwrap_or_default()),
+            participants: HashMap::new(),
+            saved_version: 0,
+            contributors: BTreeSet::new(),
+            closed: false,
+        };
+
+        // Someone else may have started it meanwhile
+        Ok(self
+            .sessions
+            .write()
+            .await
+            .entry(post_id)
+            .or_insert_with(|| Arc::new(Mutex::new(session)))
+            .clone())
+    }
+
+    /// Add an editor to a post's session. Returns the `ready` message for
+    /// the editor; document messages are sent to `sender`.
+    pub async fn join(
+        &self,
+        post_id: Uuid,
+        connection_id: Uuid,
+        user_id: Uuid,
+        name: String,
+        sender: CollabSender,
+    ) -> Result<ServerMessage> {
+        let autosave = self.newer_autosave(post_id).await?;
+        loop {
+            let session = self.session(post_id).await?;
+            let mut session = session.lock().await;
+            if session.closed {
+                // Taken down by the last editor leaving; start a new one
+                continue;
+            }
+            if session.participants.len() >= MAX_COLLABORATORS {
+                return Err(Error::validation(format!(
+                    "At most {} people can edit a post at once",
+                    MAX_COLLABORATORS
+                )));
+            }
+
+            let participant = Participant {
+                user_id,
+                name,
+                color: generate_user_color(&user_id),
+                caret: None,
+                selection: None,
+                sender,
+            };
+            let collaborator = participant.collaborator(connection_id);
+            session.participants.insert(connection_id, participant);
+            session.broadcast(ServerMessage::Joined { collaborator }, Some(connection_id));
+
+            return Ok(ServerMessage::Ready {
+                connection_id,
+                document: session.document.snapshot(),
+                collaborators: session.collaborators(),
+                autosave,
+            });
+        }
+    }
+
+    /// Remove an editor; the last one out autosaves and ends the session
+    pub async fn leave(&self, post_id: Uuid, connection_id: Uuid) {
+        let Some(session) = self.sessions.read().await.get(&post_id).cloned() else {
+            return;
+        };
+
+        let pending = {
+            let mut guard = session.lock().await;
+            if let Some(participant) = guard.participants.remove(&connection_id) {
+                guard.broadcast(
+                    ServerMessage::Left {
+                        connection_id,
+                        user_id: participant.user_id,
+                    },
+                    None,
+                );
+            }
+            if !guard.participants.is_empty() || guard.closed {
+                return;
+            }
+            guard.closed = true;
+            let mut sessions = self.sessions.write().await;
+            if sessions
+                .get(&post_id)
+                .is_some_and(|s| Arc::ptr_eq(s, &session))
+            {
+                sessions.remove(&post_id);
+            }
+            Self::pending_autosave(&mut guard)
+        };
+
+        if let Some(pending) = pending {
+            if let Err(e) = self.write_autosave(post_id, &pending).await {
+                warn!(post_id = %post_id, "Failed to autosave post: {}", e);
+            }
+        }
+    }
+
+    /// Apply an editor's change and relay it to everyone in the session,
+    /// the editor included
+    pub async fn change(
+        &self,
+        post_id: Uuid,
+        connection_id: Uuid,
+        change_id: Uuid,
+        base_version: u64,
+        ops: Vec<Operation>,
+    ) -> std::result::Result<(), DocumentError> {
+        let Some(session) = self.sessions.read().await.get(&post_id).cloned() else {
+            return Err(DocumentError::Invalid("The session has ended".to_string()));
+        };
+        let mut session = session.lock().await;
+        let Some(user_id) = session.participants.get(&connection_id).map(|p| p.user_id) else {
+            return Err(DocumentError::Invalid("Not in this session".to_string()));
+        };
+
+        let ops = session.document.apply(base_version, ops)?;
+        let applied = ServerMessage::Applied {
+            version: session.document.version(),
+            change_id,
+            user_id,
+            ops: ops.clone(),
+        };
+        if ops.is_empty() {
+            // Nothing changed, e.g. every edit was to a removed block
+            session.send(connection_id, applied);
+            return Ok(());
+        }
+
+        for participant in session.participants.values_mut() {
+            for op in &ops {
+                if let Some(caret) = participant.caret.as_mut() {
+                    caret.transform(op);
+                }
+                if let Some(selection) = participant.selection.as_mut() {
+                    selection.anchor.transform(op);
+                    selection.focus.transform(op);
+                }
+            }
+        }
+        session.contributors.insert(user_id);
+        session.broadcast(applied, None);
+        Ok(())
+    }
+
+    /// Record where an editor's caret is and show it to the others
+    pub async fn presence(
+        &self,
+        post_id: Uuid,
+        connection_id: Uuid,
+        caret: Option<Caret>,
+        selection: Option<Selection>,
+    ) {
+        let Some(session) = self.sessions.read().await.get(&post_id).cloned() else {
+            return;
+        };
+        let mut session = session.lock().await;
+        let Some(participant) = session.participants.get_mut(&connection_id) else {
+            return;
+        };
+        participant.caret = caret.clone();
+        participant.selection = selection.clone();
+        session.broadcast(
+            ServerMessage::Presence {
+                connection_id,
+                caret,
+                selection,
+            },
+            Some(connection_id),
+        );
+    }
+
+    /// The current document, for an editor that lost track of it
+    pub async fn document(&self, post_id: Uuid) -> Option<ServerMessage> {
+        let session = self.sessions.read().await.get(&post_id).cloned()?;
+        let session = session.lock().await;
+        Some(ServerMessage::Document {
+            document: session.document.snapshot(),
+        })
+    }
+
+    /// Who is editing a post
+    pub async fn status(&self, post_id: Uuid) -> SessionStatus {
+        let Some(session) = self.sessions.read().await.get(&post_id).cloned() else {
+            return SessionStatus {
+                active: false,
+                version: 0,
+                collaborators: Vec::new(),
+            };
+        };
+        let session = session.lock().await;
+        SessionStatus {
+            active: true,
+            version: session.document.version(),
+            collaborators: session.collaborators(),
+        }
+    }
+
+    fn pending_autosave(session: &mut Session) -> Option<PendingAutosave> {
+        let version = session.document.version();
+        if version <= session.saved_version {
+            return None;
+        }
+        session.saved_version = version;
+        Some(PendingAutosave {
+            version,
+            title: session.document.title().to_string(),
+            content: session.document.to_content(),
+            contributors: std::mem::take(&mut session.contributors)
+                .into_iter()
+                .collect(),
+        })
+    }
+
+    /// Autosave every document changed since its last autosave
+    pub async fn autosave(&self) -> Result<u32> {
+        let sessions: Vec<(Uuid, Arc<Mutex<Session>>)> = self
+            .sessions
+            .read()
+            .await
+            .iter()
+            .map(|(id, session)| (*id, session.clone()))
+            .collect();
+
+        let mut saved = 0;
+        for (post_id, session) in sessions {
+            let Some(pending) = Self::pending_autosave(&mut *session.lock().await) else {
+                continue;
+            };
+            match self.write_autosave(post_id, &pending).await {
+                Ok(autosave) => {
+                    saved += 1;
+                    session.lock().await.broadcast(
+                        ServerMessage::Autosaved {
+                            autosave_id: autosave.id,
+                            version: pending.version,
+                            saved_at: autosave.created_at,
+                        },
+                        None,
+                    );
+                }
+                Err(e) => {
+                    // Try again at the next interval
+                    let mut session = session.lock().await;
+                    session.saved_version = session.saved_version.min(pending.version - 1);
+                    session.contributors.extend(pending.contributors);
+                    warn!(post_id = %post_id, "Failed to autosave post: {}", e);
+                }
+            }
+        }
+        Ok(saved)
+    }
+
+    /// Autosave changed documents every `every`
+    pub fn spawn_autosave(self: &Arc<Self>, every: Duration) -> JoinHandle<()> {
+        let manager = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(every);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = manager.autosave().await {
+                    warn!("Failed to autosave documents: {}", e);
+                }
+            }
+        })
+    }
+
+    async fn write_autosave(&self, post_id: Uuid, pending: &PendingAutosave) -> Result<Autosave> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| Error::database_with_source("Failed to start transaction", e))?;
+
+        let autosave: Autosave = sqlx::query_as(
+            "INSERT INTO post_autosaves (id, post_id, version, title, content, contributors) \
+             VALUES ($1, $2, $3, $4, $5, $6) \
+             RETURNING id, post_id, version, title, content, contributors, created_at",
+        )
+        .bind(Uuid::now_v7())
+        .bind(post_id)
+        .bind(pending.version as i64)
+        .bind(&pending.title)
+        .bind(&pending.content)
+        .bind(Json(&pending.contributors))
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to write autosave", e))?;
+
+        sqlx::query(
+            "DELETE FROM post_autosaves WHERE post_id = $1 AND id NOT IN \
+             (SELECT id FROM post_autosaves WHERE post_id = $1 \
+              ORDER BY created_at DESC LIMIT $2)",
+        )
+        .bind(post_id)
+        .bind(AUTOSAVES_KEPT)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to prune autosaves", e))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| Error::database_with_source("Failed to commit autosave", e))?;
+        Ok(autosave)
+    }
+
+    /// The latest autosave, when it is newer than the saved post
+    async fn newer_autosave(&self, post_id: Uuid) -> Result<Option<AutosaveNotice>> {
+        let row: Option<(Uuid, DateTime<Utc>)> = sqlx::query_as(
+            "SELECT a.id, a.created_at FROM post_autosaves a \
+             JOIN posts p ON p.id = a.post_id \
+             WHERE a.post_id = $1 AND a.created_at > p.updated_at \
+             ORDER BY a.created_at DESC LIMIT 1",
+        )
+        .bind(post_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load autosaves", e))?;
+        Ok(row.map(|(id, created_at)| AutosaveNotice { id, created_at }))
+    }
+
+    /// Autosaves of a post, newest first
+    pub async fn autosaves(&self, post_id: Uuid) -> Result<Vec<AutosaveSummary>> {
+        sqlx::query_as(&format!(
+            "SELECT id, post_id, version, title, contributors, \
+             char_length(content) AS length, created_at \
+             FROM post_autosaves WHERE post_id = $1 \
+             AND post_id IN (SELECT id FROM posts WHERE {}) ORDER BY created_at DESC",
+            site_filter("site_id")
+        ))
+        .bind(post_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to list autosaves", e))
+    }
+
+    pub async fn get_autosave(&self, id: Uuid) -> Result<Autosave> {
+        sqlx::query_as(&format!(
+            "SELECT id, post_id, version, title, content, contributors, created_at \
+             FROM post_autosaves WHERE id = $1 AND post_id IN (SELECT id FROM posts WHERE {})",
+            site_filter("site_id")
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load autosave", e))?
+        .ok_or_else(|| Error::not_found("Autosave", id.to_string()))
+    }
+}
@@ -0,0 +1,116 @@
+//! Wire protocol for the collaborative editing socket.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::document::{Caret, DocumentSnapshot, Operation};
+
+/// Messages sent by an editor to the server
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    /// Handshake; must be the first message on a new connection
+    Auth { token: String },
+    /// A change made against `base_version`; `change_id` comes back in the
+    /// `applied` message so the editor can tell its own changes apart
+    Change {
+        change_id: Uuid,
+        base_version: u64,
+        ops: Vec<Operation>,
+    },
+    /// Where the editor's caret and selection are
+    Presence {
+        caret: Option<Caret>,
+        selection: Option<Selection>,
+    },
+    /// Send the current document again, e.g. after a `stale` error
+    Sync,
+    /// Application-level keepalive
+    Ping,
+}
+
+/// Messages sent by the server to an editor
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    /// Handshake accepted: the document and who else is editing it
+    Ready {
+        connection_id: Uuid,
+        document: DocumentSnapshot,
+        collaborators: Vec<Collaborator>,
+        /// Autosave newer than the saved post, which the editor may offer
+        /// to restore
+        autosave: Option<AutosaveNotice>,
+    },
+    /// Reply to `sync`
+    Document {
+        document: DocumentSnapshot,
+    },
+    /// A change as applied, taking the document to `version`
+    Applied {
+        version: u64,
+        change_id: Uuid,
+        user_id: Uuid,
+        ops: Vec<Operation>,
+    },
+    Joined {
+        collaborator: Collaborator,
+    },
+    Left {
+        connection_id: Uuid,
+        user_id: Uuid,
+    },
+    Presence {
+        connection_id: Uuid,
+        caret: Option<Caret>,
+        selection: Option<Selection>,
+    },
+    /// The document was written to an autosave snapshot
+    Autosaved {
+        autosave_id: Uuid,
+        version: u64,
+        saved_at: DateTime<Utc>,
+    },
+    /// Reply to a client ping
+    Pong,
+    /// Protocol, authorization or merge error
+    Error {
+        code: String,
+        message: String,
+    },
+}
+
+impl ServerMessage {
+    pub fn error(code: &str, message: impl Into<String>) -> Self {
+        Self::Error {
+            code: code.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Selected range, from where it was started to where the caret is
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Selection {
+    pub anchor: Caret,
+    pub focus: Caret,
+}
+
+/// Someone editing the document
+#[derive(Debug, Clone, Serialize)]
+pub struct Collaborator {
+    pub connection_id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub color: String,
+    pub caret: Option<Caret>,
+    pub selection: Option<Selection>,
+}
+
+/// An autosave the editor may offer to restore
+#[derive(Debug, Clone, Serialize)]
+pub struct AutosaveNotice {
+    pub id: Uuid,
+    pub created_at: DateTime<Utc>,
+}
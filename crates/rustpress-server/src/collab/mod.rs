@@ -0,0 +1,21 @@
+//! Real-time collaborative editing of posts.
+//!
+//! This module provides:
+//! - A block document model with operational transforms, so concurrent
+//!   edits to the same post converge
+//! - Per-post editing sessions with carets, selections and colors for
+//!   every editor
+//! - Periodic autosave snapshots, kept apart from the saved post
+//!
+//! Sessions live in the memory of the node the editors are connected to;
+//! in a cluster, the editors of a post must be routed to the same node.
+
+pub mod document;
+pub mod handler;
+pub mod manager;
+pub mod message;
+
+pub use document::{Caret, Document, DocumentBlock, DocumentError, DocumentSnapshot, Operation};
+pub use handler::collaborate_handler;
+pub use manager::{Autosave, AutosaveSummary, CollabManager, SessionStatus, AUTOSAVE_INTERVAL};
+pub use message::{ClientMessage, Collaborator, Selection, ServerMessage};
@@ -125,6 +125,23 @@ impl From<CoreError> for HttpError {
             CoreError::Duplicate { entity_type, field } => {
                 HttpError::conflict(format!("{} with {} already exists", entity_type, field))
             }
+            CoreError::VersionConflict {
+                entity_type,
+                id,
+                current_version,
+            } => {
+                let mut details = HashMap::new();
+                details.insert("current_version".to_string(), current_version.to_string());
+                HttpError::new(
+                    StatusCode::CONFLICT,
+                    "VERSION_CONFLICT",
+                    format!(
+                        "{} '{}' was modified by another request (current version {})",
+                        entity_type, id, current_version
+                    ),
+                )
+                .with_details(details)
+            }
             CoreError::Authentication { message } => HttpError::unauthorized(message.clone()),
             CoreError::Authorization { action, required } => HttpError::forbidden(format!(
                 "Permission '{}' required for action '{}'",
@@ -226,6 +243,29 @@ impl From<CoreError> for HttpError {
             CoreError::TenantSuspended { tenant_id } => {
                 HttpError::forbidden(format!("Tenant '{}' is suspended", tenant_id))
             }
+            CoreError::ExtensionNotAllowed { .. } => HttpError::new(
+                StatusCode::FORBIDDEN,
+                "EXTENSION_NOT_ALLOWED",
+                err.to_string(),
+            ),
+            CoreError::QuotaExceeded { resource, .. } => {
+                let mut details = HashMap::new();
+                details.insert("resource".to_string(), resource.clone());
+                HttpError::new(StatusCode::FORBIDDEN, "QUOTA_EXCEEDED", err.to_string())
+                    .with_details(details)
+            }
+            CoreError::Incompatible {
+                kind,
+                extension_id,
+                issues,
+            } => {
+                let mut details = HashMap::new();
+                details.insert("kind".to_string(), kind.clone());
+                details.insert("extension_id".to_string(), extension_id.clone());
+                details.insert("issues".to_string(), issues.join("\n"));
+                HttpError::new(StatusCode::CONFLICT, "INCOMPATIBLE", err.to_string())
+                    .with_details(details)
+            }
             CoreError::Hook { hook_name, message } => {
                 tracing::error!("Hook error ({}): {}", hook_name, message);
                 HttpError::internal_error("A hook error occurred")
@@ -2,12 +2,13 @@
 
 use axum::{
     async_trait,
-    extract::{FromRef, FromRequestParts, Path, Query},
+    extract::{FromRef, FromRequestParts, OriginalUri, Path, Query},
     http::{header, request::Parts, HeaderMap, StatusCode},
     Json,
 };
-use rustpress_auth::{Claims, JwtManager};
+use rustpress_auth::{ApiKeyScope, Claims, JwtManager, TokenType};
 use rustpress_core::context::RequestContext;
+use rustpress_core::tenant::current_site;
 use rustpress_core::types::Pagination;
 use serde::de::DeserializeOwned;
 use serde::Deserialize;
@@ -16,6 +17,9 @@ use uuid::Uuid;
 use validator::Validate;
 
 use crate::error::HttpError;
+use crate::services::http_signatures::SIGNATURE_KEY_CLAIM;
+use crate::services::sites::NETWORK_ADMIN_ROLE;
+use crate::services::{user_api_keys, GroupGrants, VerifiedSignature};
 use crate::state::AppState;
 
 /// Authenticated user extracted from JWT
@@ -23,11 +27,125 @@ use crate::state::AppState;
 pub struct AuthUser {
     pub id: Uuid,
     pub email: Option<String>,
+    /// The user's own role followed by any granted by their groups
     pub roles: Vec<String>,
     pub claims: Claims,
+    /// What the user's groups grant them
+    pub groups: Arc<GroupGrants>,
 }
 
 impl AuthUser {
+    /// Merge in what the user's groups grant. Failing to load them only
+    /// withholds the grants, so the request goes ahead with the user's own
+    /// role.
+    pub async fn with_group_grants(mut self, app_state: &AppState) -> Self {
+        match app_state.groups.grants_for(self.id).await {
+            Ok(grants) => {
+                for role in &grants.roles {
+                    if !self.has_role(role) {
+                        self.roles.push(role.clone());
+                    }
+                }
+                self.groups = grants;
+            }
+            Err(e) => {
+                tracing::warn!(user_id = %self.id, "Failed to load group grants: {}", e);
+            }
+        }
+        self
+    }
+
+    /// User for validated access token claims, with their group grants
+    pub async fn from_claims(claims: Claims, app_state: &AppState) -> Result<Self, HttpError> {
+        // Parse user ID from subject
+        let id = Uuid::parse_str(&claims.sub)
+            .map_err(|_| HttpError::unauthorized("Invalid user ID in token"))?;
+
+        // Get email from custom claims if present
+        let email = claims
+            .custom
+            .get("email")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string());
+
+        // Convert role to roles vector
+        let roles: Vec<String> = claims.role.iter().cloned().collect();
+
+        let user = AuthUser {
+            id,
+            email,
+            roles,
+            claims,
+            groups: Arc::default(),
+        }
+        .with_group_grants(app_state)
+        .await;
+        user.check_site_member(app_state).await?;
+        Ok(user)
+    }
+
+    /// User a trusted integration acts as, by its verified signature: whoever
+    /// registered its key
+    pub async fn from_signature(
+        signature: &VerifiedSignature,
+        app_state: &AppState,
+    ) -> Result<Self, HttpError> {
+        let owner = app_state
+            .http_signatures
+            .key_owner(signature)
+            .await?
+            .ok_or_else(|| HttpError::unauthorized("The signing key acts for no active user"))?;
+
+        let claims = Claims::new(owner.user_id.to_string(), "rustpress", TokenType::Access)
+            .with_role(owner.role.clone())
+            .with_custom("email", serde_json::json!(owner.email))
+            .with_custom(SIGNATURE_KEY_CLAIM, serde_json::json!(signature.key_id));
+
+        let user = AuthUser {
+            id: owner.user_id,
+            email: Some(owner.email),
+            roles: vec![owner.role],
+            claims,
+            groups: Arc::default(),
+        }
+        .with_group_grants(app_state)
+        .await;
+        user.check_site_member(app_state).await?;
+        Ok(user)
+    }
+
+    /// Refuse users who don't belong to the site the request is served
+    /// for. Everyone belongs to the main site, and network admins to every
+    /// site.
+    pub async fn check_site_member(&self, app_state: &AppState) -> Result<(), HttpError> {
+        let Some(site) = current_site() else {
+            return Ok(());
+        };
+        if self.has_role(NETWORK_ADMIN_ROLE) || app_state.sites.is_member(site, self.id).await? {
+            Ok(())
+        } else {
+            Err(HttpError::forbidden("You are not a member of this site"))
+        }
+    }
+
+    /// Id of the user API key the request was made with, if any
+    pub fn api_key_id(&self) -> Option<Uuid> {
+        self.claims
+            .custom
+            .get(user_api_keys::API_KEY_CLAIM)
+            .and_then(|v| v.as_str())
+            .and_then(|v| Uuid::parse_str(v).ok())
+    }
+
+    /// Scopes of the user API key the request was made with, if any
+    pub fn api_key_scopes(&self) -> Option<Vec<String>> {
+        self.api_key_id()?;
+        self.claims
+            .custom
+            .get("scopes")
+            .and_then(|v| serde_json::from_value(v.clone()).ok())
+    }
+
     pub fn has_role(&self, role: &str) -> bool {
         self.roles.iter().any(|r| r == role)
     }
@@ -35,6 +153,12 @@ impl AuthUser {
     pub fn is_admin(&self) -> bool {
         self.has_role("administrator")
     }
+
+    /// Whether a capability granted by one of the user's groups covers
+    /// `resource:action`
+    pub fn has_capability(&self, resource: &str, action: &str) -> bool {
+        self.groups.has_capability(resource, action)
+    }
 }
 
 #[async_trait]
@@ -48,9 +172,53 @@ where
     async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
         let app_state = AppState::from_ref(state);
 
-        // Extract token from Authorization header
-        let token = extract_bearer_token(&parts.headers)
-            .ok_or_else(|| HttpError::unauthorized("Missing authorization header"))?;
+        // Extract token from Authorization header; integrations may sign
+        // their requests instead
+        let Some(token) = extract_bearer_token(&parts.headers) else {
+            return match parts.extensions.get::<VerifiedSignature>() {
+                Some(signature) => AuthUser::from_signature(signature, &app_state).await,
+                None => Err(HttpError::unauthorized("Missing authorization header")),
+            };
+        };
+
+        // User API keys act as their owner within the key's scopes
+        if user_api_keys::is_user_api_key(&token) {
+            let owner = app_state
+                .user_api_keys
+                .authenticate(&token)
+                .await?
+                .ok_or_else(|| HttpError::unauthorized("Invalid, expired or revoked API key"))?;
+
+            if let Some(scope) = api_key_scope(parts) {
+                if !owner.allows(&scope) {
+                    return Err(HttpError::forbidden(format!(
+                        "API key does not grant {}",
+                        scope
+                    )));
+                }
+            }
+
+            let claims = Claims::new(owner.user_id.to_string(), "rustpress", TokenType::Access)
+                .with_role(owner.role.clone())
+                .with_custom("email", serde_json::json!(owner.email))
+                .with_custom(
+                    user_api_keys::API_KEY_CLAIM,
+                    serde_json::json!(owner.key_id),
+                )
+                .with_custom("scopes", serde_json::json!(owner.scopes.0));
+
+            let user = AuthUser {
+                id: owner.user_id,
+                email: Some(owner.email),
+                roles: vec![owner.role],
+                claims,
+                groups: Arc::default(),
+            }
+            .with_group_grants(&app_state)
+            .await;
+            user.check_site_member(&app_state).await?;
+            return Ok(user);
+        }
 
         // Validate token
         let claims = app_state
@@ -58,29 +226,22 @@ where
             .validate_access_token(&token)
             .map_err(|_| HttpError::unauthorized("Invalid or expired token"))?;
 
-        // Parse user ID from subject
-        let id = Uuid::parse_str(&claims.sub)
-            .map_err(|_| HttpError::unauthorized("Invalid user ID in token"))?;
-
-        // Get email from custom claims if present
-        let email = claims
-            .custom
-            .get("email")
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string());
-
-        // Convert role to roles vector
-        let roles: Vec<String> = claims.role.iter().cloned().collect();
-
-        Ok(AuthUser {
-            id,
-            email,
-            roles,
-            claims,
-        })
+        AuthUser::from_claims(claims, &app_state).await
     }
 }
 
+/// Scope a user API key needs for a request
+///
+/// Nested routers only see the rest of the path, so the scope is taken
+/// from the path the request was sent to.
+fn api_key_scope(parts: &Parts) -> Option<ApiKeyScope> {
+    let path = parts
+        .extensions
+        .get::<OriginalUri>()
+        .map_or_else(|| parts.uri.path(), |uri| uri.0.path());
+    user_api_keys::required_scope(&parts.method, path)
+}
+
 /// Optional authenticated user (doesn't fail if no auth)
 #[derive(Debug, Clone)]
 pub struct MaybeAuthUser(pub Option<AuthUser>);
@@ -220,6 +381,68 @@ where
     }
 }
 
+/// `If-Match` precondition carrying the entity version a client last read.
+///
+/// Accepts ETags produced by [`crate::response::Versioned`] (`"3"`, `W/"3"`)
+/// as well as bare numbers. A missing header or `*` yields `None`, which
+/// leaves the update unguarded.
+pub struct IfMatch(pub Option<i64>);
+
+impl IfMatch {
+    /// Parse an `If-Match` header value into a version
+    pub fn parse(value: &str) -> Option<Option<i64>> {
+        let value = value.trim();
+        if value == "*" {
+            return Some(None);
+        }
+        let tag = value.strip_prefix("W/").unwrap_or(value).trim_matches('"');
+        tag.parse::<i64>().ok().map(Some)
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for IfMatch
+where
+    S: Send + Sync,
+{
+    type Rejection = HttpError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let Some(value) = parts.headers.get(header::IF_MATCH) else {
+            return Ok(IfMatch(None));
+        };
+
+        value
+            .to_str()
+            .ok()
+            .and_then(IfMatch::parse)
+            .map(IfMatch)
+            .ok_or_else(|| HttpError::bad_request("Invalid If-Match header"))
+    }
+}
+
+/// Request signed by a trusted integration, verified by the
+/// [`crate::middleware::http_signatures`] layer
+#[derive(Debug, Clone)]
+pub struct SignedRequest(pub VerifiedSignature);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for SignedRequest
+where
+    S: Send + Sync,
+{
+    type Rejection = HttpError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<VerifiedSignature>()
+            .cloned()
+            .map(SignedRequest)
+            .ok_or_else(|| HttpError::unauthorized("Request must carry an HTTP message signature"))
+    }
+}
+
 /// UUID path parameter extractor
 pub struct PathId(pub Uuid);
 
@@ -257,7 +480,9 @@ pub async fn require_permission(
     resource: &str,
     action: &str,
 ) -> Result<(), HttpError> {
-    if !app_state.permissions.can(&user.roles, resource, action) {
+    if !app_state.permissions.can(&user.roles, resource, action)
+        && !user.has_capability(resource, action)
+    {
         return Err(HttpError::forbidden(format!(
             "Missing permission: {}:{}",
             resource, action
@@ -345,6 +570,43 @@ mod tests {
         assert_eq!(pagination.total, 100);
     }
 
+    #[test]
+    fn test_if_match_parse() {
+        assert_eq!(IfMatch::parse("\"3\""), Some(Some(3)));
+        assert_eq!(IfMatch::parse("W/\"12\""), Some(Some(12)));
+        assert_eq!(IfMatch::parse("5"), Some(Some(5)));
+        assert_eq!(IfMatch::parse("*"), Some(None));
+        assert_eq!(IfMatch::parse("\"abc\""), None);
+    }
+
+    #[tokio::test]
+    async fn test_api_key_scope_uses_the_full_path() {
+        use axum::{body::Body, http::Request, routing::post, Router};
+        use tower::ServiceExt;
+
+        async fn scope(parts: Parts) -> String {
+            api_key_scope(&parts)
+                .map(|s| s.to_string())
+                .unwrap_or_default()
+        }
+
+        // Nested the way the API routes are
+        let router = Router::new().nest(
+            "/api/v1",
+            Router::new().nest("/posts", Router::new().route("/:id", post(scope))),
+        );
+        let request = Request::builder()
+            .method("POST")
+            .uri("/api/v1/posts/42")
+            .body(Body::empty())
+            .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"posts:write");
+    }
+
     #[test]
     fn test_pagination_offset_limit() {
         let params = PaginationParams {
@@ -4,18 +4,26 @@
 
 pub mod app;
 pub mod background;
+pub mod collab;
 pub mod error;
 pub mod extract;
 pub mod metrics;
 pub mod middleware;
+pub mod middleware_stack;
+pub mod plugin_routes;
 pub mod response;
 pub mod routes;
 pub mod security;
 pub mod services;
 pub mod setup;
 pub mod shutdown;
+pub mod startup;
 pub mod state;
+pub mod transaction;
+#[cfg(feature = "wasm-plugins")]
+pub mod wasm_plugins;
 pub mod websocket;
+pub mod ws;
 
 pub use app::App;
 pub use background::init_background_tasks;
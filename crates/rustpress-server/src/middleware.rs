@@ -1,17 +1,46 @@
 //! HTTP middleware implementations.
 
 use axum::{
-    body::Body,
-    extract::State,
-    http::{header, Method, Request, StatusCode},
+    body::{Body, HttpBody},
+    extract::{MatchedPath, State},
+    http::{header, Method, Request, StatusCode, Uri},
     middleware::Next,
     response::{IntoResponse, Response},
 };
+use futures::StreamExt;
+use rustpress_core::config::TenantIdentification;
+use rustpress_core::fault::FaultInjector;
+use rustpress_core::tenant::{self, MeteredResource, UsageMeter};
+use rustpress_database::repository::sites::SiteRow;
+use std::net::IpAddr;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tower_http::cors::{Any, CorsLayer};
 use tracing::{info, warn, Span};
 use uuid::Uuid;
 
+use crate::error::HttpError;
+use crate::extract::MaybeAuthUser;
+use crate::security::TrafficClass;
+use crate::services::abuse_challenge::{
+    COMMENT_ENDPOINT, PASSWORD_RESET_ENDPOINT, REGISTRATION_ENDPOINT,
+};
+use crate::services::cache_policy::{CacheHints, CacheOverride, CacheRequest, CacheVisibility};
+use crate::services::compliance::{ContentDescriptor, AGE_GATE_COOKIE};
+use crate::services::feeds::etag_matches;
+use crate::services::http_signatures::{
+    SignatureError, SignatureTarget, ACCEPT_SIGNATURE, MAX_SIGNED_BODY_BYTES,
+};
+use crate::services::page_cache::{shared_max_age, CachedPage, PageCacheConfig};
+use crate::services::public_api::{
+    query_api_key, response_cache_key, RequestOutcome, PUBLIC_API_CACHE_TTL,
+};
+use crate::services::read_only::ReadOnlyService;
+use crate::services::redirects::encode_location;
+use crate::services::sites::{
+    site_address, strip_site_prefix, CurrentSite, SiteAddress, SiteStatus, SITE_HEADER,
+};
+use crate::services::usage::UsageService;
 use crate::state::AppState;
 
 /// Request ID middleware - adds unique ID to each request
@@ -115,6 +144,23 @@ pub struct RateLimitInfo {
     pub reset_at: Instant,
 }
 
+/// Per-country limit for a client, when one is configured and GeoIP knows the IP
+fn country_limit(
+    state: &AppState,
+    rate_limit: &rustpress_core::config::RateLimitConfig,
+    ip: Option<IpAddr>,
+) -> Option<u32> {
+    if rate_limit.country_requests_per_window.is_empty() {
+        return None;
+    }
+    let country = state.geoip.lookup(ip?)?.country_code?;
+    rate_limit
+        .country_requests_per_window
+        .iter()
+        .find(|(code, _)| code.eq_ignore_ascii_case(&country))
+        .map(|(_, limit)| *limit)
+}
+
 /// Simple in-memory rate limiter (for production, use Redis)
 pub async fn rate_limit(
     State(state): State<AppState>,
@@ -122,12 +168,9 @@ pub async fn rate_limit(
     next: Next,
 ) -> Response {
     // Get client identifier (IP address)
-    let client_ip = request
-        .headers()
-        .get("x-forwarded-for")
-        .and_then(|v| v.to_str().ok())
-        .and_then(|s| s.split(',').next())
-        .map(|s| s.trim().to_string())
+    let ip = client_ip(&request, &state.config.server.trusted_proxies);
+    let client_ip = ip
+        .map(|ip| ip.to_string())
         .unwrap_or_else(|| "unknown".to_string());
 
     let rate_limit = &state.config.rate_limit;
@@ -145,8 +188,23 @@ pub async fn rate_limit(
         }
     }
 
-    // Simple rate limit check using cache
-    let cache_key = format!("rate_limit:{}", client_ip);
+    // Bots get their own buckets so they never eat into the allowance of
+    // humans sharing an IP, and suspected bots get a much smaller one
+    let class = TrafficClass::of(&request);
+    let (cache_key, limit) = match class {
+        TrafficClass::Human => (
+            format!("rate_limit:{}", client_ip),
+            country_limit(&state, rate_limit, ip).unwrap_or(rate_limit.requests_per_window),
+        ),
+        TrafficClass::KnownBot => (
+            format!("rate_limit:{}:{}", class.as_str(), client_ip),
+            rate_limit.known_bot_requests_per_window,
+        ),
+        TrafficClass::SuspectedBot => (
+            format!("rate_limit:{}:{}", class.as_str(), client_ip),
+            rate_limit.suspected_bot_requests_per_window,
+        ),
+    };
     let current_count: u32 = state
         .cache
         .get(&cache_key)
@@ -154,7 +212,7 @@ pub async fn rate_limit(
         .unwrap_or(Some(0))
         .unwrap_or(0);
 
-    if current_count >= rate_limit.requests_per_window {
+    if current_count >= limit {
         let mut response = Response::new(Body::from("Too many requests"));
         *response.status_mut() = StatusCode::TOO_MANY_REQUESTS;
         response
@@ -176,19 +234,15 @@ pub async fn rate_limit(
     let mut response = next.run(request).await;
 
     // Add rate limit headers
-    response.headers_mut().insert(
-        "x-ratelimit-limit",
-        rate_limit.requests_per_window.to_string().parse().unwrap(),
-    );
+    response
+        .headers_mut()
+        .insert("x-ratelimit-limit", limit.to_string().parse().unwrap());
     response.headers_mut().insert(
         "x-ratelimit-remaining",
-        (rate_limit
-            .requests_per_window
-            .saturating_sub(current_count)
-            .saturating_sub(1))
-        .to_string()
-        .parse()
-        .unwrap(),
+        (limit.saturating_sub(current_count).saturating_sub(1))
+            .to_string()
+            .parse()
+            .unwrap(),
     );
 
     response
@@ -378,13 +432,24 @@ pub fn compression_layer() -> tower_http::compression::CompressionLayer {
 pub async fn body_limit(request: Request<Body>, next: Next) -> Result<Response, StatusCode> {
     const MAX_BODY_SIZE: u64 = 10 * 1024 * 1024; // 10MB
 
+    // WordPress export files routinely exceed the API limit
+    let max_body_size = if request
+        .uri()
+        .path()
+        .starts_with(crate::services::wordpress_import::IMPORT_PATH)
+    {
+        crate::services::MAX_WXR_SIZE as u64
+    } else {
+        MAX_BODY_SIZE
+    };
+
     if let Some(content_length) = request
         .headers()
         .get(header::CONTENT_LENGTH)
         .and_then(|v| v.to_str().ok())
         .and_then(|s| s.parse::<u64>().ok())
     {
-        if content_length > MAX_BODY_SIZE {
+        if content_length > max_body_size {
             return Err(StatusCode::PAYLOAD_TOO_LARGE);
         }
     }
@@ -411,46 +476,1054 @@ pub async fn api_version(request: Request<Body>, next: Next) -> Response {
     response
 }
 
-/// Tenant identification middleware for multi-tenancy
-pub async fn tenant_identification(
+/// Public submission routes that can require a CAPTCHA, with their endpoint name
+const CAPTCHA_ROUTES: &[(&str, &str)] = &[
+    ("/api/v1/comments", COMMENT_ENDPOINT),
+    ("/api/v1/auth/register", REGISTRATION_ENDPOINT),
+    ("/api/v1/auth/forgot-password", PASSWORD_RESET_ENDPOINT),
+];
+
+/// Largest body buffered for CAPTCHA verification
+const MAX_CAPTCHA_BODY_SIZE: usize = 1024 * 1024;
+
+/// CAPTCHA verification for public submission endpoints
+///
+/// Signed-in users are not challenged. The body is buffered to read the
+/// token and handed on unchanged.
+pub async fn captcha_verification(
     State(state): State<AppState>,
-    mut request: Request<Body>,
+    request: Request<Body>,
     next: Next,
 ) -> Response {
-    // Skip if multi-tenancy is disabled
-    if !state.config.multitenancy.enabled {
+    if request.method() != Method::POST {
+        return next.run(request).await;
+    }
+    let path = request.uri().path().trim_end_matches('/');
+    let Some(endpoint) = CAPTCHA_ROUTES
+        .iter()
+        .find(|(route, _)| *route == path)
+        .map(|(_, endpoint)| *endpoint)
+    else {
+        return next.run(request).await;
+    };
+    if !state.captcha.is_required(endpoint).await {
         return next.run(request).await;
     }
 
-    // Try to identify tenant from subdomain or header
-    let tenant_id = request
+    let remote_ip =
+        client_ip(&request, &state.config.server.trusted_proxies).map(|ip| ip.to_string());
+    let (mut parts, body) = request.into_parts();
+    if let Ok(MaybeAuthUser(Some(_))) =
+        axum::extract::FromRequestParts::from_request_parts(&mut parts, &state).await
+    {
+        return next.run(Request::from_parts(parts, body)).await;
+    }
+
+    let bytes = match axum::body::to_bytes(body, MAX_CAPTCHA_BODY_SIZE).await {
+        Ok(bytes) => bytes,
+        Err(_) => return StatusCode::PAYLOAD_TOO_LARGE.into_response(),
+    };
+    // Non-JSON bodies are left for the handler to reject
+    let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap_or_default();
+
+    match state
+        .captcha
+        .verify(endpoint, &json, remote_ip.as_deref())
+        .await
+    {
+        Ok(_) => {
+            next.run(Request::from_parts(parts, Body::from(bytes)))
+                .await
+        }
+        Err(failure) => {
+            let status = if failure == crate::services::CaptchaFailure::Unavailable {
+                StatusCode::SERVICE_UNAVAILABLE
+            } else {
+                StatusCode::FORBIDDEN
+            };
+            HttpError::new(status, "CAPTCHA_FAILED", failure.to_string())
+                .with_details(std::collections::HashMap::from([(
+                    "reason".to_string(),
+                    failure.as_str().to_string(),
+                )]))
+                .into_response()
+        }
+    }
+}
+
+/// Client address of a request
+///
+/// `X-Forwarded-For` is only honoured when the connection comes from a
+/// trusted proxy; the client is then the right-most address in the chain
+/// that is not itself a trusted proxy.
+pub(crate) fn client_ip(request: &Request<Body>, trusted_proxies: &[IpAddr]) -> Option<IpAddr> {
+    let peer = request
+        .extensions()
+        .get::<axum::extract::ConnectInfo<std::net::SocketAddr>>()
+        .map(|info| info.0.ip())?;
+    if !trusted_proxies.contains(&peer) {
+        return Some(peer);
+    }
+
+    let forwarded: Vec<IpAddr> = request
         .headers()
-        .get("x-tenant-id")
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map_while(|s| s.trim().parse().ok())
+        .collect();
+    Some(
+        forwarded
+            .into_iter()
+            .rev()
+            .find(|ip| !trusted_proxies.contains(ip))
+            .unwrap_or(peer),
+    )
+}
+
+/// Whether a request comes straight from a trusted proxy
+fn from_trusted_proxy(request: &Request<Body>, trusted_proxies: &[IpAddr]) -> bool {
+    request
+        .extensions()
+        .get::<axum::extract::ConnectInfo<std::net::SocketAddr>>()
+        .is_some_and(|info| trusted_proxies.contains(&info.0.ip()))
+}
+
+/// Stored redirects for public pages
+///
+/// Answers GET and HEAD requests whose path matches an enabled redirect
+/// rule with the rule's redirect, or with 410 Gone for removed content,
+/// before the request is routed.
+pub async fn redirects(
+    State(state): State<AppState>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let path = request.uri().path();
+    if !matches!(*request.method(), Method::GET | Method::HEAD)
+        || path.starts_with("/api/")
+        || path.starts_with("/admin")
+        || path.starts_with("/themes/")
+    {
+        return next.run(request).await;
+    }
+
+    let redirect = match state.redirects.resolve(path).await {
+        Ok(Some(redirect)) => redirect,
+        Ok(None) => return next.run(request).await,
+        Err(e) => {
+            warn!(path, "Failed to resolve redirect: {}", e);
+            return next.run(request).await;
+        }
+    };
+
+    if redirect.is_gone() {
+        return (
+            StatusCode::GONE,
+            axum::response::Html(notice_page("Gone", "<p>This content has been removed.</p>")),
+        )
+            .into_response();
+    }
+
+    let location = if redirect.target.starts_with('/') {
+        encode_location(&redirect.target)
+    } else {
+        redirect.target
+    };
+    let status = StatusCode::from_u16(redirect.status_code)
+        .ok()
+        .filter(|status| status.is_redirection())
+        .unwrap_or(StatusCode::MOVED_PERMANENTLY);
+    (status, [(header::LOCATION, location)]).into_response()
+}
+
+/// Regional compliance rules for public pages
+///
+/// Withholds content blocked in the visitor's region (451) and puts
+/// age-gated content behind a confirmation page until the visitor holds a
+/// matching age cookie. Pages subject to region-dependent rules are marked
+/// private so edge caches never share them across regions.
+pub async fn compliance(
+    State(state): State<AppState>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let path = request.uri().path();
+    if !matches!(*request.method(), Method::GET | Method::HEAD)
+        || path.starts_with("/api/")
+        || path.starts_with("/admin")
+        || path.starts_with("/themes/")
+    {
+        return next.run(request).await;
+    }
+
+    let engine = state.compliance.engine().await;
+    if !engine.config().enabled {
+        return next.run(request).await;
+    }
+
+    let client_ip = client_ip(&request, &state.config.server.trusted_proxies);
+    let confirmed_age = request
+        .headers()
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(name, _)| *name == AGE_GATE_COOKIE)
+        .and_then(|(_, value)| value.parse::<u8>().ok());
+
+    let mut response = next.run(request).await;
+    let Some(content) = response.extensions().get::<ContentDescriptor>().cloned() else {
+        return response;
+    };
+    if !engine.is_region_dependent(&content) {
+        return response;
+    }
+
+    let region = state.compliance.region_for(client_ip);
+    let decision = engine.evaluate(&region, Some(&content));
+
+    if let Some(blocked) = decision.blocked {
+        info!(
+            rule_id = %blocked.rule_id,
+            country = ?region.country_code,
+            "Content withheld by compliance rule"
+        );
+        let message = blocked
+            .message
+            .unwrap_or_else(|| "This content is not available in your region.".to_string());
+        return (
+            StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS,
+            [(header::CACHE_CONTROL, "private, no-store")],
+            axum::response::Html(notice_page(
+                "Unavailable For Legal Reasons",
+                &format!("<p>{}</p>", html_escape(&message)),
+            )),
+        )
+            .into_response();
+    }
+
+    if let Some(min_age) = decision.age_gate {
+        if confirmed_age.filter(|age| *age >= min_age).is_none() {
+            let body = format!(
+                r#"<p>You must be {min_age} or older to view this content.</p>
+<button onclick="document.cookie='{AGE_GATE_COOKIE}={min_age}; path=/; max-age=2592000; SameSite=Lax'; location.reload();">I am {min_age} or older</button>"#
+            );
+            let mut gate =
+                axum::response::Html(notice_page("Age verification", &body)).into_response();
+            gate.extensions_mut().insert(CacheHints {
+                surrogate_keys: Vec::new(),
+                cache_override: Some(CacheOverride {
+                    visibility: Some(CacheVisibility::NoStore),
+                    ..Default::default()
+                }),
+            });
+            return gate;
+        }
+    }
+
+    // Same URL, different answer per region: keep it out of shared caches
+    let mut hints = response
+        .extensions_mut()
+        .remove::<CacheHints>()
+        .unwrap_or_default();
+    hints
+        .cache_override
+        .get_or_insert_with(CacheOverride::default)
+        .visibility = Some(CacheVisibility::Private);
+    response.extensions_mut().insert(hints);
+    response
+}
+
+fn notice_page(title: &str, body: &str) -> String {
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head><meta charset="utf-8"><meta name="robots" content="noindex"><title>{title}</title></head>
+<body style="font-family: sans-serif; max-width: 32rem; margin: 4rem auto; text-align: center;">
+<h1>{title}</h1>
+{body}
+</body>
+</html>"#
+    )
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Edge cache policy for public GET responses
+///
+/// Replaces the handler's `Cache-Control` with the configured policy and
+/// adds `Surrogate-Control`, `Surrogate-Key` and `Cache-Tag` headers.
+/// Authenticated and preview requests are never shared.
+pub async fn cache_policy(
+    State(state): State<AppState>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let path = request.uri().path().to_string();
+    if !matches!(*request.method(), Method::GET | Method::HEAD)
+        || path.starts_with("/api/")
+        || path.starts_with("/admin")
+    {
+        return next.run(request).await;
+    }
+    let personalized = request.headers().contains_key(header::AUTHORIZATION)
+        || request
+            .uri()
+            .query()
+            .is_some_and(|q| q.split('&').any(|pair| pair.starts_with("preview=")));
+
+    let mut response = next.run(request).await;
+
+    let engine = state.cache_policy.engine().await;
+    let content_type = response
+        .headers()
+        .get(header::CONTENT_TYPE)
         .and_then(|v| v.to_str().ok())
-        .map(|s| s.to_string())
+        .unwrap_or_default()
+        .to_string();
+    let cache_request = CacheRequest {
+        path: &path,
+        content_type: &content_type,
+        status: response.status().as_u16(),
+        personalized: personalized || response.headers().contains_key(header::SET_COOKIE),
+    };
+    let Some(resolved) = engine.resolve(&cache_request, response.extensions().get::<CacheHints>())
+    else {
+        return response;
+    };
+
+    let headers = response.headers_mut();
+    if let Ok(value) = resolved.cache_control.parse() {
+        headers.insert(header::CACHE_CONTROL, value);
+    }
+    if let Some(value) = resolved.surrogate_control.and_then(|v| v.parse().ok()) {
+        headers.insert("surrogate-control", value);
+    }
+    if !resolved.surrogate_keys.is_empty() {
+        if let Ok(value) = resolved.surrogate_keys.join(" ").parse() {
+            headers.insert("surrogate-key", value);
+        }
+        if let Ok(value) = resolved.surrogate_keys.join(",").parse() {
+            headers.insert("cache-tag", value);
+        }
+    }
+    response
+}
+
+/// Full-page cache for anonymous public pages
+///
+/// Serves stored responses without reaching the handler and stores
+/// rendered pages the cache policy marked public, tagged with their
+/// surrogate keys so content changes purge only affected pages.
+pub async fn page_cache(
+    State(state): State<AppState>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let config = state.page_cache.config().await;
+    let path = request.uri().path();
+    if !config.enabled
+        || !matches!(*request.method(), Method::GET | Method::HEAD)
+        || path.starts_with("/api/")
+        || path.starts_with("/admin")
+    {
+        return next.run(request).await;
+    }
+
+    let headers = request.headers();
+    let bypass = headers.contains_key(header::AUTHORIZATION)
+        || request
+            .uri()
+            .query()
+            .is_some_and(|q| q.split('&').any(|pair| pair.starts_with("preview=")))
+        || config.has_bypass_cookie(
+            headers
+                .get_all(header::COOKIE)
+                .iter()
+                .filter_map(|v| v.to_str().ok()),
+        );
+    if bypass {
+        return next.run(request).await;
+    }
+
+    let mut host = headers
+        .get(header::HOST)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+    // Sites may share a host and be told apart by header or path prefix
+    if let Some(scope) = cache_scope(&request) {
+        host = format!("{}#{}", host, scope);
+    }
+    let key = config.cache_key(&host, path, request.uri().query(), |name| {
+        headers.get(name).and_then(|v| v.to_str().ok())
+    });
+
+    if let Some(page) = state.page_cache.lookup(&key).await {
+        // Stored pages keep their ETag, so readers polling feeds still get 304s
+        let not_modified = headers
+            .get(header::IF_NONE_MATCH)
+            .and_then(|v| v.to_str().ok())
+            .zip(page.headers.iter().find(|(name, _)| name == "etag"))
+            .is_some_and(|(tags, (_, etag))| etag_matches(tags, etag));
+        let mut response = if not_modified {
+            let mut response = Response::new(Body::empty());
+            *response.status_mut() = StatusCode::NOT_MODIFIED;
+            response
+        } else {
+            let mut response = Response::new(Body::from(page.body()));
+            *response.status_mut() = StatusCode::from_u16(page.status).unwrap_or(StatusCode::OK);
+            response
+        };
+        let headers = response.headers_mut();
+        for (name, value) in &page.headers {
+            if let (Ok(name), Ok(value)) = (
+                header::HeaderName::from_bytes(name.as_bytes()),
+                header::HeaderValue::from_str(value),
+            ) {
+                headers.append(name, value);
+            }
+        }
+        headers.insert(header::AGE, header::HeaderValue::from(page.age()));
+        headers.insert("x-page-cache", header::HeaderValue::from_static("HIT"));
+        return response;
+    }
+
+    let storable = request.method() == Method::GET;
+    let rendered_at = chrono::Utc::now().timestamp_millis();
+    let mut response = next.run(request).await;
+    response
+        .headers_mut()
+        .insert("x-page-cache", header::HeaderValue::from_static("MISS"));
+
+    if !storable {
+        return response;
+    }
+    let Some(ttl) = page_ttl(&response, &config) else {
+        return response;
+    };
+
+    // Compressed bodies carry no length, so the size is checked once buffered
+    let (parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            warn!("Failed to buffer page for caching: {}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to read response body",
+            )
+                .into_response();
+        }
+    };
+
+    if bytes.len() > config.max_body_bytes {
+        return Response::from_parts(parts, Body::from(bytes));
+    }
+
+    let keys = parts
+        .headers
+        .get("surrogate-key")
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.split_whitespace().map(str::to_string).collect())
+        .unwrap_or_default();
+    let page = CachedPage::new(
+        parts.status.as_u16(),
+        parts.headers.iter().filter_map(|(name, value)| {
+            value
+                .to_str()
+                .ok()
+                .map(|value| (name.as_str().to_string(), value.to_string()))
+        }),
+        &bytes,
+        keys,
+        rendered_at,
+    );
+    let page_cache = state.page_cache.clone();
+    tokio::spawn(async move {
+        page_cache.store(&key, &page, ttl).await;
+    });
+
+    Response::from_parts(parts, Body::from(bytes))
+}
+
+/// How long a response may be kept in the page cache, if at all
+fn page_ttl(response: &Response, config: &PageCacheConfig) -> Option<Duration> {
+    let headers = response.headers();
+    if response.status() != StatusCode::OK
+        || response.extensions().get::<CacheHints>().is_none()
+        || headers.contains_key(header::SET_COOKIE)
+    {
+        return None;
+    }
+
+    // Only variants the cache key distinguishes can be stored
+    let varies_on_unknown = headers
+        .get_all(header::VARY)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(str::trim)
+        .any(|name| {
+            !config
+                .vary_headers
+                .iter()
+                .any(|vary| vary.eq_ignore_ascii_case(name))
+        });
+    if varies_on_unknown {
+        return None;
+    }
+
+    let too_large = headers
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<usize>().ok())
+        .is_some_and(|len| len > config.max_body_bytes);
+    if too_large {
+        return None;
+    }
+
+    let max_age = headers
+        .get(header::CACHE_CONTROL)
+        .and_then(|v| v.to_str().ok())
+        .and_then(shared_max_age)?;
+    let ttl = max_age.min(config.ttl_secs);
+    (ttl > 0).then(|| Duration::from_secs(ttl.into()))
+}
+
+/// Public API key check, rate limiting, response cache and usage accounting
+///
+/// Runs as a route layer of the public API router, so the matched route
+/// template is known and usage is counted per route rather than per URL.
+/// Successful responses are shared between keys in the server's own cache;
+/// cache hits still count against the key's limits.
+pub async fn public_api(
+    State(state): State<AppState>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let secret = request
+        .headers()
+        .get("x-api-key")
+        .and_then(|v| v.to_str().ok())
+        .or_else(|| query_api_key(request.uri().query()))
+        .map(str::to_string);
+    let Some(secret) = secret else {
+        return HttpError::unauthorized("A public API key is required").into_response();
+    };
+    let key = match state.public_api.authenticate(&secret).await {
+        Ok(Some(key)) => key,
+        Ok(None) => return HttpError::unauthorized("Invalid API key").into_response(),
+        Err(e) => return HttpError::from(e).into_response(),
+    };
+
+    let origin = request
+        .headers()
+        .get(header::ORIGIN)
+        .and_then(|v| v.to_str().ok());
+    if !key.allows_origin(origin) {
+        return HttpError::forbidden("This API key cannot be used from this origin")
+            .into_response();
+    }
+
+    let endpoint = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| request.uri().path().to_string());
+
+    let rate = state.public_api.check_rate(&key).await;
+    if let Some(retry_after_secs) = rate.retry_after_secs {
+        state
+            .public_api
+            .record(key.id, &endpoint, RequestOutcome::RateLimited, 0);
+        let mut response =
+            HttpError::from(rustpress_core::error::Error::RateLimited { retry_after_secs })
+                .into_response();
+        response.headers_mut().insert(
+            header::RETRY_AFTER,
+            header::HeaderValue::from(retry_after_secs),
+        );
+        rate_limit_headers(&mut response, rate.limit, 0);
+        return response;
+    }
+
+    let mut cache_key = response_cache_key(request.uri().path(), request.uri().query());
+    if let Some(scope) = cache_scope(&request) {
+        cache_key = format!("{}#{}", cache_key, scope);
+    }
+
+    if let Some(page) = state.public_api.cached_response(&cache_key).await {
+        let body = page.body();
+        state.public_api.record(
+            key.id,
+            &endpoint,
+            RequestOutcome::Served {
+                status: page.status,
+                cache_hit: true,
+            },
+            body.len() as u64,
+        );
+        let mut response = Response::new(Body::from(body));
+        let headers = response.headers_mut();
+        for (name, value) in &page.headers {
+            if let (Ok(name), Ok(value)) = (
+                header::HeaderName::from_bytes(name.as_bytes()),
+                header::HeaderValue::from_str(value),
+            ) {
+                headers.append(name, value);
+            }
+        }
+        headers.insert(header::AGE, header::HeaderValue::from(page.age()));
+        headers.insert("x-cache", header::HeaderValue::from_static("HIT"));
+        rate_limit_headers(&mut response, rate.limit, rate.remaining);
+        return response;
+    }
+
+    let rendered_at = chrono::Utc::now().timestamp_millis();
+    let response = next.run(request).await;
+    let (mut parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            warn!("Failed to buffer public API response: {}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to read response body",
+            )
+                .into_response();
+        }
+    };
+
+    let status = parts.status;
+    if status == StatusCode::OK {
+        // A shared cache would hand the response to callers without a key,
+        // past the key check and its limits, so only the client may keep it
+        parts.headers.insert(
+            header::CACHE_CONTROL,
+            header::HeaderValue::from_str(&format!(
+                "private, max-age={}, stale-while-revalidate={}",
+                PUBLIC_API_CACHE_TTL.as_secs(),
+                PUBLIC_API_CACHE_TTL.as_secs() * 2
+            ))
+            .expect("valid cache-control header"),
+        );
+        let page = CachedPage::new(
+            status.as_u16(),
+            parts.headers.iter().filter_map(|(name, value)| {
+                value
+                    .to_str()
+                    .ok()
+                    .map(|value| (name.as_str().to_string(), value.to_string()))
+            }),
+            &bytes,
+            Vec::new(),
+            rendered_at,
+        );
+        state
+            .public_api
+            .store_response(&cache_key, &page, bytes.len())
+            .await;
+    } else {
+        parts.headers.insert(
+            header::CACHE_CONTROL,
+            header::HeaderValue::from_static("no-store"),
+        );
+    }
+    parts
+        .headers
+        .insert("x-cache", header::HeaderValue::from_static("MISS"));
+
+    state.public_api.record(
+        key.id,
+        &endpoint,
+        RequestOutcome::Served {
+            status: status.as_u16(),
+            cache_hit: false,
+        },
+        bytes.len() as u64,
+    );
+    let mut response = Response::from_parts(parts, Body::from(bytes));
+    rate_limit_headers(&mut response, rate.limit, rate.remaining);
+    response
+}
+
+fn rate_limit_headers(response: &mut Response, limit: u32, remaining: u32) {
+    let headers = response.headers_mut();
+    headers.insert("x-ratelimit-limit", header::HeaderValue::from(limit));
+    headers.insert(
+        "x-ratelimit-remaining",
+        header::HeaderValue::from(remaining),
+    );
+}
+
+/// HTTP message signature middleware. Requests carrying a `Signature-Input`
+/// header are verified and rejected when the signature does not hold; valid
+/// ones reach handlers with a [`crate::services::VerifiedSignature`]
+/// extension. Unsigned requests pass through untouched.
+pub async fn http_signatures(
+    State(state): State<AppState>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    if !request.headers().contains_key("signature-input") {
+        return next.run(request).await;
+    }
+
+    let (mut parts, body) = request.into_parts();
+    let body = match axum::body::to_bytes(body, MAX_SIGNED_BODY_BYTES).await {
+        Ok(body) => body,
+        Err(_) => {
+            return HttpError::new(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                "PAYLOAD_TOO_LARGE",
+                "Signed request body is too large",
+            )
+            .into_response()
+        }
+    };
+
+    let scheme = parts
+        .uri
+        .scheme_str()
+        .or_else(|| {
+            parts
+                .headers
+                .get("x-forwarded-proto")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.split(',').next())
+                .map(str::trim)
+        })
+        .unwrap_or(if state.config.server.tls_enabled {
+            "https"
+        } else {
+            "http"
+        })
+        .to_string();
+    let authority = parts
+        .uri
+        .authority()
+        .map(|a| a.as_str())
         .or_else(|| {
-            // Try to extract from Host header (subdomain)
-            request
-                .headers()
+            parts
+                .headers
                 .get(header::HOST)
                 .and_then(|v| v.to_str().ok())
-                .and_then(|host| {
-                    let parts: Vec<&str> = host.split('.').collect();
-                    if parts.len() > 2 {
-                        Some(parts[0].to_string())
-                    } else {
-                        None
-                    }
-                })
-        });
+        })
+        .unwrap_or_default()
+        .to_string();
+    let target = SignatureTarget::new(
+        parts.method.as_str(),
+        scheme,
+        authority,
+        parts.uri.path(),
+        parts.uri.query().map(str::to_string),
+    )
+    .with_fields(
+        parts
+            .headers
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_bytes())),
+    );
 
-    if let Some(tenant_id) = tenant_id {
-        request.extensions_mut().insert(TenantId(tenant_id));
+    match state.http_signatures.verify(&target, &body).await {
+        Ok(verified) => {
+            parts.extensions.insert(verified);
+            next.run(Request::from_parts(parts, Body::from(body))).await
+        }
+        Err(SignatureError::Unavailable) => {
+            HttpError::service_unavailable(SignatureError::Unavailable.to_string()).into_response()
+        }
+        Err(e) => {
+            warn!(error = %e, path = %parts.uri.path(), "Rejected HTTP message signature");
+            let mut response = HttpError::unauthorized(e.to_string()).into_response();
+            response.headers_mut().insert(
+                "accept-signature",
+                header::HeaderValue::from_static(ACCEPT_SIGNATURE),
+            );
+            response
+        }
     }
+}
+
+/// Largest GraphQL body inspected in read-only mode
+const MAX_GRAPHQL_BODY_SIZE: usize = 1024 * 1024;
 
+/// Read-only maintenance mode
+///
+/// Refuses writes with `503 Service Unavailable` and a `Retry-After` header
+/// while the mode is on. Reads, and the endpoints needed to sign in and
+/// leave the mode, go through. GraphQL bodies are buffered so queries are
+/// served and only mutations refused.
+pub async fn read_only(
+    State(state): State<AppState>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    if !state.read_only.is_enabled()
+        || ReadOnlyService::allows(request.method(), request.uri().path())
+    {
+        return next.run(request).await;
+    }
+    if ReadOnlyService::is_graphql(request.method(), request.uri().path()) {
+        let (parts, body) = request.into_parts();
+        let bytes = match axum::body::to_bytes(body, MAX_GRAPHQL_BODY_SIZE).await {
+            Ok(bytes) => bytes,
+            Err(_) => return StatusCode::PAYLOAD_TOO_LARGE.into_response(),
+        };
+        if ReadOnlyService::graphql_reads_only(&bytes) {
+            return next
+                .run(Request::from_parts(parts, Body::from(bytes)))
+                .await;
+        }
+    }
+
+    let status = state.read_only.status();
+    let message = status.reason.clone().unwrap_or_else(|| {
+        "The site is in read-only maintenance mode; please try again later".to_string()
+    });
+    let mut response = HttpError::new(StatusCode::SERVICE_UNAVAILABLE, "READ_ONLY", message)
+        .with_details(std::collections::HashMap::from([(
+            "retry_after".to_string(),
+            status.retry_after_secs.to_string(),
+        )]))
+        .into_response();
+    response.headers_mut().insert(
+        header::RETRY_AFTER,
+        header::HeaderValue::from(status.retry_after_secs),
+    );
+    response
+}
+
+/// Fault injection scope
+///
+/// Tags the request with its path so route-targeted fault rules apply to
+/// the database, cache, storage and HTTP calls made while serving it.
+pub async fn fault_scope(
+    State(state): State<AppState>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    if !state.faults.is_allowed() {
+        return next.run(request).await;
+    }
+    let path = request.uri().path().to_string();
+    FaultInjector::scope(path, next.run(request)).await
+}
+
+/// Address of the site a request names by host, header or path prefix
+///
+/// Read before resolving the site, so that no borrow of the request is
+/// held across an await.
+fn request_site_address(
+    state: &AppState,
+    request: &Request<Body>,
+    with_prefix: bool,
+) -> Result<Option<SiteAddress>, Response> {
+    let (headers, uri) = (request.headers(), request.uri());
+    let header = headers.get(SITE_HEADER).and_then(|v| v.to_str().ok());
+    // Only a proxy in front of the network may pick the site by header
+    if header.is_some()
+        && state.config.multitenancy.identification == TenantIdentification::Header
+        && !from_trusted_proxy(request, &state.config.server.trusted_proxies)
+    {
+        return Err(HttpError::forbidden(format!(
+            "The {} header is only accepted from trusted proxies",
+            SITE_HEADER
+        ))
+        .into_response());
+    }
+    let host = headers
+        .get(header::HOST)
+        .and_then(|v| v.to_str().ok())
+        .or_else(|| uri.host());
+    let Some(mut address) = site_address(&state.config.multitenancy, host, uri.path(), header)
+    else {
+        return Ok(None);
+    };
+    if !with_prefix && address.prefix.take().is_some() {
+        address.slug = None;
+        if address.domain.is_none() {
+            return Ok(None);
+        }
+    }
+    Ok(Some(address))
+}
+
+/// Site a request names by host, header or path prefix
+///
+/// Requests naming no site, and path prefixes matching no site, are
+/// served by the main site. Unknown subdomains and headers answer 404.
+async fn resolve_site(
+    state: &AppState,
+    address: Option<SiteAddress>,
+) -> Result<Option<(SiteRow, Option<String>)>, Response> {
+    let Some(address) = address else {
+        return Ok(None);
+    };
+
+    match state.sites.resolve(&address).await {
+        Ok(Some(site)) => {
+            let prefix = address
+                .prefix
+                .filter(|_| address.slug.as_ref() == Some(&site.slug));
+            Ok(Some((site, prefix)))
+        }
+        Ok(None) if address.names_site() => {
+            Err(HttpError::not_found("No site is served at this address").into_response())
+        }
+        Ok(None) => Ok(None),
+        Err(e) => Err(HttpError::from(e).into_response()),
+    }
+}
+
+/// Strip the path prefix naming a site, before routing
+///
+/// Runs around the whole router with path identification, so sites
+/// answer on the main site's routes below their prefix.
+pub async fn site_path_prefix(
+    State(state): State<AppState>,
+    mut request: Request<Body>,
+    next: Next,
+) -> Response {
+    let address = match request_site_address(&state, &request, true) {
+        Ok(address) => address,
+        Err(response) => return response,
+    };
+    let (site, prefix) = match resolve_site(&state, address).await {
+        Ok(Some((site, Some(prefix)))) => (site, prefix),
+        Ok(_) => return next.run(request).await,
+        Err(response) => return response,
+    };
+
+    let uri = request.uri();
+    let path = strip_site_prefix(uri.path(), &prefix).unwrap_or("/");
+    let path_and_query = match uri.query() {
+        Some(query) => format!("{}?{}", path, query),
+        None => path.to_string(),
+    };
+    let mut parts = uri.clone().into_parts();
+    parts.path_and_query = path_and_query.parse().ok();
+    if let Ok(stripped) = Uri::from_parts(parts) {
+        *request.uri_mut() = stripped;
+    }
+
+    request.extensions_mut().insert(CurrentSite(site));
     next.run(request).await
 }
 
+/// Tenant identification middleware for multi-tenancy
+///
+/// Resolves the site of the network the request is for, refuses
+/// suspended and archived sites and sites out of bandwidth outside of the
+/// network API, and serves the request on the site's behalf, counting the
+/// bytes of the response against the site's bandwidth.
+pub async fn tenant_identification(
+    State(state): State<AppState>,
+    mut request: Request<Body>,
+    next: Next,
+) -> Response {
+    // Skip if multi-tenancy is disabled
+    if !state.config.multitenancy.enabled {
+        return next.run(request).await;
+    }
+
+    let current = request
+        .extensions()
+        .get::<CurrentSite>()
+        .map(|CurrentSite(site)| site.clone());
+    let site = match current {
+        Some(site) => Some(site),
+        None => {
+            let address = match request_site_address(&state, &request, false) {
+                Ok(address) => address,
+                Err(response) => return response,
+            };
+            match resolve_site(&state, address).await {
+                Ok(site) => site.map(|(site, _)| site),
+                Err(response) => return response,
+            }
+        }
+    };
+
+    let Some(site) = site else {
+        if let Some(tenant) = &state.config.multitenancy.default_tenant {
+            request.extensions_mut().insert(TenantId(tenant.clone()));
+        }
+        return next.run(request).await;
+    };
+
+    // Network admins manage suspended and archived sites from any of them
+    let network_api = request.uri().path().starts_with("/api/v1/network");
+    if !network_api {
+        match SiteStatus::parse(&site.status) {
+            SiteStatus::Active => {}
+            SiteStatus::Suspended => {
+                return HttpError::service_unavailable("This site is suspended").into_response()
+            }
+            SiteStatus::Archived => {
+                return HttpError::new(StatusCode::GONE, "SITE_ARCHIVED", "This site is archived")
+                    .into_response()
+            }
+        }
+    }
+
+    let current = CurrentSite(site);
+    request
+        .extensions_mut()
+        .insert(TenantId(current.tenant().to_string()));
+    let id = current.0.id;
+    request.extensions_mut().insert(current);
+
+    if !network_api {
+        if let Err(e) = state.usage.check(id, MeteredResource::Bandwidth, 1).await {
+            return HttpError::from(e).into_response();
+        }
+    }
+
+    let response = tenant::with_site(Some(id), next.run(request)).await;
+    meter_bandwidth(&state.usage, id, response)
+}
+
+/// Count the bytes of a site's response against its bandwidth, as they
+/// are sent when the size is not known up front
+fn meter_bandwidth(usage: &Arc<UsageService>, site: Uuid, response: Response) -> Response {
+    let (parts, body) = response.into_parts();
+    if let Some(size) = body.size_hint().exact() {
+        usage.add(site, MeteredResource::Bandwidth, size as i64);
+        return Response::from_parts(parts, body);
+    }
+
+    let usage = usage.clone();
+    let counted = body.into_data_stream().inspect(move |chunk| {
+        if let Ok(chunk) = chunk {
+            usage.add(site, MeteredResource::Bandwidth, chunk.len() as i64);
+        }
+    });
+    Response::from_parts(parts, Body::from_stream(counted))
+}
+
+/// Key segment telling apart the caches of sites and tenants sharing a host
+fn cache_scope(request: &Request<Body>) -> Option<String> {
+    let extensions = request.extensions();
+    match (
+        extensions.get::<CurrentSite>(),
+        extensions.get::<TenantId>(),
+    ) {
+        (Some(CurrentSite(site)), _) => Some(site.id.to_string()),
+        (None, Some(TenantId(tenant))) => Some(tenant.clone()),
+        (None, None) => None,
+    }
+}
+
 /// Tenant ID wrapper
 #[derive(Clone, Debug)]
 pub struct TenantId(pub String);
@@ -465,6 +1538,32 @@ mod tests {
         assert_eq!(id.0, "test-123");
     }
 
+    #[test]
+    fn test_client_ip_trusts_only_configured_proxies() {
+        let proxy: IpAddr = "10.0.0.1".parse().unwrap();
+        let request =
+            |peer: &str| {
+                let mut request = Request::builder()
+                    .header("x-forwarded-for", "203.0.113.9, 198.51.100.7, 10.0.0.1")
+                    .body(Body::empty())
+                    .unwrap();
+                request.extensions_mut().insert(
+                    axum::extract::ConnectInfo::<std::net::SocketAddr>(peer.parse().unwrap()),
+                );
+                request
+            };
+
+        assert_eq!(
+            client_ip(&request("192.0.2.1:4000"), &[proxy]),
+            Some("192.0.2.1".parse().unwrap())
+        );
+        assert_eq!(
+            client_ip(&request("10.0.0.1:4000"), &[proxy]),
+            Some("198.51.100.7".parse().unwrap())
+        );
+        assert_eq!(client_ip(&request("10.0.0.1:4000"), &[]), Some(proxy));
+    }
+
     #[test]
     fn test_tenant_id_wrapper() {
         let id = TenantId("tenant-456".to_string());
@@ -0,0 +1,445 @@
+//! Middleware stack composition
+//!
+//! Resolves the `[middleware]` configuration into the ordered middleware
+//! each route group runs. [`App::build_router`](crate::App::build_router)
+//! layers the router accordingly, and `rustpress --print-middleware`
+//! prints the same plan without starting the server.
+
+use std::fmt;
+use std::str::FromStr;
+
+use rustpress_core::config::{MiddlewareConfig, MiddlewareStackConfig};
+
+/// A middleware the stack can be composed of
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MiddlewareKind {
+    FaultScope,
+    TenantIdentification,
+    Redirects,
+    PageCache,
+    CachePolicy,
+    Compliance,
+    Captcha,
+    BotDetection,
+    PageViewAnalytics,
+    RateLimit,
+    ReadOnly,
+    ApiVersion,
+    BodyLimit,
+    Cors,
+    ContentSecurity,
+    RequestValidation,
+    SecurityHeaders,
+    RequestLogging,
+    Fingerprint,
+    SecurityAudit,
+    RequestId,
+    HttpSignatures,
+    Transactions,
+    Compression,
+    Tracing,
+}
+
+impl MiddlewareKind {
+    /// Built-in order, outermost first
+    pub const DEFAULT_ORDER: [MiddlewareKind; 25] = [
+        Self::FaultScope,
+        Self::TenantIdentification,
+        Self::Redirects,
+        Self::PageCache,
+        Self::CachePolicy,
+        Self::Compliance,
+        Self::Captcha,
+        Self::BotDetection,
+        Self::PageViewAnalytics,
+        Self::RateLimit,
+        Self::ReadOnly,
+        Self::ApiVersion,
+        Self::BodyLimit,
+        Self::Cors,
+        Self::ContentSecurity,
+        Self::RequestValidation,
+        Self::SecurityHeaders,
+        Self::RequestLogging,
+        Self::Fingerprint,
+        Self::SecurityAudit,
+        Self::RequestId,
+        Self::HttpSignatures,
+        Self::Transactions,
+        Self::Compression,
+        Self::Tracing,
+    ];
+
+    /// Pairs where the first middleware must wrap the second when both
+    /// are enabled, because the second reads what the first attaches
+    const MUST_WRAP: [(MiddlewareKind, MiddlewareKind); 3] = [
+        (Self::BotDetection, Self::RateLimit),
+        (Self::BotDetection, Self::PageViewAnalytics),
+        (Self::TenantIdentification, Self::PageCache),
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::FaultScope => "fault_scope",
+            Self::TenantIdentification => "tenant_identification",
+            Self::Redirects => "redirects",
+            Self::PageCache => "page_cache",
+            Self::CachePolicy => "cache_policy",
+            Self::Compliance => "compliance",
+            Self::Captcha => "captcha",
+            Self::BotDetection => "bot_detection",
+            Self::PageViewAnalytics => "page_view_analytics",
+            Self::RateLimit => "rate_limit",
+            Self::ReadOnly => "read_only",
+            Self::ApiVersion => "api_version",
+            Self::BodyLimit => "body_limit",
+            Self::Cors => "cors",
+            Self::ContentSecurity => "content_security",
+            Self::RequestValidation => "request_validation",
+            Self::SecurityHeaders => "security_headers",
+            Self::RequestLogging => "request_logging",
+            Self::Fingerprint => "fingerprint",
+            Self::SecurityAudit => "security_audit",
+            Self::RequestId => "request_id",
+            Self::HttpSignatures => "http_signatures",
+            Self::Transactions => "transactions",
+            Self::Compression => "compression",
+            Self::Tracing => "tracing",
+        }
+    }
+
+    /// Middleware that protects the server and cannot be disabled
+    pub fn is_required(&self) -> bool {
+        matches!(
+            self,
+            Self::TenantIdentification
+                | Self::ReadOnly
+                | Self::BodyLimit
+                | Self::ContentSecurity
+                | Self::RequestValidation
+                | Self::SecurityAudit
+                | Self::RequestId
+        )
+    }
+}
+
+impl FromStr for MiddlewareKind {
+    type Err = MiddlewarePlanError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Self::DEFAULT_ORDER
+            .into_iter()
+            .find(|kind| kind.name() == name)
+            .ok_or_else(|| MiddlewarePlanError::Unknown(name.to_string()))
+    }
+}
+
+impl fmt::Display for MiddlewareKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Invalid middleware configuration
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum MiddlewarePlanError {
+    #[error("Unknown middleware '{0}'")]
+    Unknown(String),
+
+    #[error("Middleware group '{group}': '{middleware}' is required and cannot be disabled")]
+    Required { group: String, middleware: String },
+
+    #[error("Middleware group '{group}': '{middleware}' is listed more than once")]
+    Duplicate { group: String, middleware: String },
+
+    #[error("Middleware group '{group}': order leaves out '{middleware}'; disable it instead")]
+    Missing { group: String, middleware: String },
+
+    #[error("Middleware group '{group}': '{outer}' must come before '{inner}'")]
+    Misordered {
+        group: String,
+        outer: String,
+        inner: String,
+    },
+
+    #[error("Middleware group '{group}': path prefix '{prefix}' must start with '/'")]
+    InvalidPrefix { group: String, prefix: String },
+
+    #[error("Middleware group '{0}' is defined more than once")]
+    DuplicateGroup(String),
+}
+
+/// Middleware of one route group, outermost first
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupPlan {
+    pub name: String,
+    /// Path prefix the group applies to; `None` for the default stack
+    pub path_prefix: Option<String>,
+    pub stack: Vec<MiddlewareKind>,
+}
+
+impl GroupPlan {
+    fn resolve(
+        name: &str,
+        path_prefix: Option<String>,
+        config: &MiddlewareStackConfig,
+    ) -> Result<Self, MiddlewarePlanError> {
+        let disabled = config
+            .disable
+            .iter()
+            .map(|name| name.parse())
+            .collect::<Result<Vec<MiddlewareKind>, _>>()?;
+        if let Some(kind) = disabled.iter().find(|kind| kind.is_required()) {
+            return Err(MiddlewarePlanError::Required {
+                group: name.to_string(),
+                middleware: kind.to_string(),
+            });
+        }
+
+        let stack: Vec<MiddlewareKind> = if config.order.is_empty() {
+            MiddlewareKind::DEFAULT_ORDER
+                .into_iter()
+                .filter(|kind| !disabled.contains(kind))
+                .collect()
+        } else {
+            let order = config
+                .order
+                .iter()
+                .map(|name| name.parse())
+                .collect::<Result<Vec<MiddlewareKind>, _>>()?;
+            for (i, kind) in order.iter().enumerate() {
+                if order[..i].contains(kind) || disabled.contains(kind) {
+                    return Err(MiddlewarePlanError::Duplicate {
+                        group: name.to_string(),
+                        middleware: kind.to_string(),
+                    });
+                }
+            }
+            // Nothing is dropped silently: leaving middleware out of an
+            // explicit order takes a matching `disable` entry
+            if let Some(kind) = MiddlewareKind::DEFAULT_ORDER
+                .into_iter()
+                .find(|kind| !order.contains(kind) && !disabled.contains(kind))
+            {
+                return Err(MiddlewarePlanError::Missing {
+                    group: name.to_string(),
+                    middleware: kind.to_string(),
+                });
+            }
+            order
+        };
+
+        let position = |kind: MiddlewareKind| stack.iter().position(|k| *k == kind);
+        for (outer, inner) in MiddlewareKind::MUST_WRAP {
+            if let (Some(o), Some(i)) = (position(outer), position(inner)) {
+                if o > i {
+                    return Err(MiddlewarePlanError::Misordered {
+                        group: name.to_string(),
+                        outer: outer.to_string(),
+                        inner: inner.to_string(),
+                    });
+                }
+            }
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            path_prefix,
+            stack,
+        })
+    }
+
+    /// Whether `path` falls under the group's prefix, on a segment boundary
+    fn matches(&self, path: &str) -> bool {
+        let Some(prefix) = self.path_prefix.as_deref() else {
+            return true;
+        };
+        let prefix = prefix.trim_end_matches('/');
+        match path.strip_prefix(prefix) {
+            Some(rest) => rest.is_empty() || rest.starts_with('/') || prefix.is_empty(),
+            None => false,
+        }
+    }
+}
+
+/// Effective middleware stacks of every route group
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MiddlewarePlan {
+    pub default: GroupPlan,
+    /// Groups longest prefix first, so the first match wins
+    pub groups: Vec<GroupPlan>,
+}
+
+impl MiddlewarePlan {
+    pub fn from_config(config: &MiddlewareConfig) -> Result<Self, MiddlewarePlanError> {
+        let default = GroupPlan::resolve("default", None, &config.default)?;
+
+        let mut groups = Vec::with_capacity(config.groups.len());
+        for group in &config.groups {
+            if !group.path_prefix.starts_with('/') {
+                return Err(MiddlewarePlanError::InvalidPrefix {
+                    group: group.name.clone(),
+                    prefix: group.path_prefix.clone(),
+                });
+            }
+            if group.name == "default" || groups.iter().any(|g: &GroupPlan| g.name == group.name) {
+                return Err(MiddlewarePlanError::DuplicateGroup(group.name.clone()));
+            }
+            groups.push(GroupPlan::resolve(
+                &group.name,
+                Some(group.path_prefix.clone()),
+                &group.stack,
+            )?);
+        }
+        groups.sort_by_key(|g| std::cmp::Reverse(g.path_prefix.as_ref().map_or(0, String::len)));
+
+        Ok(Self { default, groups })
+    }
+
+    /// Group whose stack handles `path`
+    pub fn resolve(&self, path: &str) -> &GroupPlan {
+        self.groups
+            .iter()
+            .find(|group| group.matches(path))
+            .unwrap_or(&self.default)
+    }
+}
+
+impl Default for MiddlewarePlan {
+    fn default() -> Self {
+        Self::from_config(&MiddlewareConfig::default()).expect("built-in order is valid")
+    }
+}
+
+impl fmt::Display for GroupPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.path_prefix {
+            Some(prefix) => writeln!(f, "{} ({})", self.name, prefix)?,
+            None => writeln!(f, "{} (all other routes)", self.name)?,
+        }
+        for (i, kind) in self.stack.iter().enumerate() {
+            writeln!(f, "  {:>2}. {}", i + 1, kind)?;
+        }
+        let disabled: Vec<_> = MiddlewareKind::DEFAULT_ORDER
+            .into_iter()
+            .filter(|kind| !self.stack.contains(kind))
+            .map(|kind| kind.name())
+            .collect();
+        if !disabled.is_empty() {
+            writeln!(f, "      disabled: {}", disabled.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for MiddlewarePlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Middleware order per route group, outermost first:")?;
+        for group in &self.groups {
+            writeln!(f)?;
+            write!(f, "{}", group)?;
+        }
+        writeln!(f)?;
+        write!(f, "{}", self.default)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustpress_core::config::MiddlewareGroupConfig;
+
+    fn group(name: &str, prefix: &str, order: &[&str], disable: &[&str]) -> MiddlewareGroupConfig {
+        MiddlewareGroupConfig {
+            name: name.to_string(),
+            path_prefix: prefix.to_string(),
+            stack: MiddlewareStackConfig {
+                order: order.iter().map(|s| s.to_string()).collect(),
+                disable: disable.iter().map(|s| s.to_string()).collect(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_default_plan_keeps_built_in_order() {
+        let plan = MiddlewarePlan::default();
+        assert_eq!(plan.default.stack, MiddlewareKind::DEFAULT_ORDER.to_vec());
+        assert_eq!(plan.resolve("/api/v1/posts").name, "default");
+    }
+
+    #[test]
+    fn test_groups_resolve_by_longest_prefix() {
+        let config = MiddlewareConfig {
+            default: MiddlewareStackConfig::default(),
+            groups: vec![
+                group("api", "/api", &[], &["page_cache", "cache_policy"]),
+                group("webhooks", "/api/v1/webhooks", &[], &["rate_limit"]),
+            ],
+        };
+        let plan = MiddlewarePlan::from_config(&config).unwrap();
+
+        assert_eq!(plan.resolve("/api/v1/posts").name, "api");
+        assert_eq!(plan.resolve("/api/v1/webhooks/stripe").name, "webhooks");
+        assert_eq!(plan.resolve("/apis").name, "default");
+        assert!(!plan
+            .resolve("/api")
+            .stack
+            .contains(&MiddlewareKind::PageCache));
+    }
+
+    #[test]
+    fn test_explicit_order() {
+        let mut order: Vec<&str> = MiddlewareKind::DEFAULT_ORDER
+            .iter()
+            .map(|k| k.name())
+            .filter(|name| *name != "compression")
+            .collect();
+        order.insert(0, "compression");
+
+        let config = MiddlewareConfig {
+            default: MiddlewareStackConfig::default(),
+            groups: vec![group("public", "/", &order, &[])],
+        };
+        let plan = MiddlewarePlan::from_config(&config).unwrap();
+        assert_eq!(plan.resolve("/blog").stack[0], MiddlewareKind::Compression);
+        assert!(plan.to_string().contains("public (/)"));
+    }
+
+    #[test]
+    fn test_invalid_configurations() {
+        let plan = |groups| {
+            MiddlewarePlan::from_config(&MiddlewareConfig {
+                default: MiddlewareStackConfig::default(),
+                groups,
+            })
+        };
+
+        assert!(matches!(
+            plan(vec![group("a", "/a", &[], &["gzip"])]),
+            Err(MiddlewarePlanError::Unknown(_))
+        ));
+        assert!(matches!(
+            plan(vec![group("a", "/a", &[], &["request_id"])]),
+            Err(MiddlewarePlanError::Required { .. })
+        ));
+        assert!(matches!(
+            plan(vec![group("a", "/a", &["compression"], &[])]),
+            Err(MiddlewarePlanError::Missing { .. })
+        ));
+        assert!(matches!(
+            plan(vec![group("a", "a", &[], &[])]),
+            Err(MiddlewarePlanError::InvalidPrefix { .. })
+        ));
+
+        let mut order: Vec<&str> = MiddlewareKind::DEFAULT_ORDER
+            .iter()
+            .map(|k| k.name())
+            .filter(|name| *name != "bot_detection")
+            .collect();
+        order.push("bot_detection");
+        assert!(matches!(
+            plan(vec![group("a", "/a", &order, &[])]),
+            Err(MiddlewarePlanError::Misordered { .. })
+        ));
+    }
+}
@@ -0,0 +1,589 @@
+//! Plugin routers.
+//!
+//! Plugins contribute HTTP routes by implementing [`RoutablePlugin`]. Each
+//! plugin's routes are mounted under `/plugins/{id}/`, run against a
+//! [`PluginScope`] instead of the full application state, and declare the
+//! authentication they need so the host enforces it before the handler
+//! runs. Routes are checked for conflicts when they are registered and are
+//! listed in the API's OpenAPI document.
+//!
+//! Plugins register their routes while they are loaded, which may be after
+//! the server built its router, so `/plugins` dispatches each request to
+//! the plugin's router at the time it arrives.
+
+use axum::{
+    body::Body,
+    extract::{FromRequestParts, State},
+    handler::Handler,
+    http::{Method, Request, StatusCode},
+    middleware::{self as axum_middleware, Next},
+    response::{IntoResponse, Response},
+    routing::{on, MethodFilter, MethodRouter},
+    Router,
+};
+use parking_lot::RwLock;
+use rustpress_cache::Cache;
+use rustpress_core::error::{Error, Result};
+use rustpress_core::http::HttpClient;
+use rustpress_core::plugin::{PluginManager, PluginState};
+use rustpress_events::EventBus;
+use serde_json::json;
+use sqlx::PgPool;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use tower::ServiceExt;
+
+use crate::error::HttpError;
+use crate::extract::AuthUser;
+use crate::state::AppState;
+
+/// Path plugin routers are mounted under
+pub const PLUGIN_ROUTES_PREFIX: &str = "/plugins";
+
+/// Authentication a plugin route requires
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RouteAuth {
+    /// Anyone may call the route
+    Public,
+    /// Any signed-in user
+    Authenticated,
+    /// Signed-in users with the role; administrators always pass
+    Role(String),
+}
+
+impl RouteAuth {
+    pub fn role(role: impl Into<String>) -> Self {
+        Self::Role(role.into())
+    }
+
+    /// Whether `user` satisfies the requirement
+    pub fn allows(&self, user: Option<&AuthUser>) -> bool {
+        match (self, user) {
+            (Self::Public, _) => true,
+            (_, None) => false,
+            (Self::Authenticated, Some(_)) => true,
+            (Self::Role(role), Some(user)) => user.is_admin() || user.has_role(role),
+        }
+    }
+}
+
+/// Definition of a plugin route, used for auth, conflict detection and
+/// the OpenAPI document
+#[derive(Debug, Clone)]
+pub struct PluginRoute {
+    pub method: Method,
+    /// Path below the plugin prefix in axum syntax, e.g. `/items/:id`
+    pub path: String,
+    pub auth: RouteAuth,
+    pub summary: Option<String>,
+}
+
+impl PluginRoute {
+    /// Route requiring a signed-in user; relax it with [`public`](Self::public)
+    pub fn new(method: Method, path: impl Into<String>) -> Self {
+        Self {
+            method,
+            path: path.into(),
+            auth: RouteAuth::Authenticated,
+            summary: None,
+        }
+    }
+
+    pub fn get(path: impl Into<String>) -> Self {
+        Self::new(Method::GET, path)
+    }
+
+    pub fn post(path: impl Into<String>) -> Self {
+        Self::new(Method::POST, path)
+    }
+
+    pub fn put(path: impl Into<String>) -> Self {
+        Self::new(Method::PUT, path)
+    }
+
+    pub fn patch(path: impl Into<String>) -> Self {
+        Self::new(Method::PATCH, path)
+    }
+
+    pub fn delete(path: impl Into<String>) -> Self {
+        Self::new(Method::DELETE, path)
+    }
+
+    pub fn public(mut self) -> Self {
+        self.auth = RouteAuth::Public;
+        self
+    }
+
+    pub fn role(mut self, role: impl Into<String>) -> Self {
+        self.auth = RouteAuth::role(role);
+        self
+    }
+
+    pub fn summary(mut self, summary: impl Into<String>) -> Self {
+        self.summary = Some(summary.into());
+        self
+    }
+
+    /// Path with parameter names dropped, so `/items/:id` and
+    /// `/items/:slug` compare equal
+    fn shape(&self) -> String {
+        self.path
+            .split('/')
+            .map(|segment| match segment.chars().next() {
+                Some(':') => ":",
+                Some('*') => "*",
+                _ => segment,
+            })
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// Path in OpenAPI syntax, e.g. `/items/{id}`
+    fn openapi_path(&self) -> String {
+        self.path
+            .split('/')
+            .map(|segment| match segment.strip_prefix([':', '*']) {
+                Some(name) => format!("{{{}}}", name),
+                None => segment.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    fn validate(&self) -> std::result::Result<(), String> {
+        if !self.path.starts_with('/') {
+            return Err(format!("route path `{}` must start with `/`", self.path));
+        }
+        if self.path.split('/').any(|s| s == ":" || s == "*") {
+            return Err(format!(
+                "route path `{}` has an unnamed parameter",
+                self.path
+            ));
+        }
+        if MethodFilter::try_from(self.method.clone()).is_err() {
+            return Err(format!("method {} is not supported", self.method));
+        }
+        Ok(())
+    }
+}
+
+/// Application state a plugin's handlers see, scoped to that plugin
+#[derive(Clone)]
+pub struct PluginScope {
+    plugin_id: Arc<str>,
+    db: PgPool,
+    cache: Arc<Cache>,
+    events: Arc<EventBus>,
+    http: HttpClient,
+}
+
+impl PluginScope {
+    /// Scope for `plugin_id` sharing the services of `state`
+    pub fn new(plugin_id: &str, state: &AppState) -> Self {
+        Self {
+            plugin_id: Arc::from(plugin_id),
+            db: state.db().inner().clone(),
+            cache: state.cache.clone(),
+            events: state.event_bus.clone(),
+            http: state.http.clone(),
+        }
+    }
+
+    pub fn plugin_id(&self) -> &str {
+        &self.plugin_id
+    }
+
+    pub fn db(&self) -> &PgPool {
+        &self.db
+    }
+
+    pub fn cache(&self) -> &Cache {
+        &self.cache
+    }
+
+    /// Cache key in the plugin's namespace
+    pub fn cache_key(&self, key: &str) -> String {
+        format!("plugin:{}:{}", self.plugin_id, key)
+    }
+
+    pub fn events(&self) -> &EventBus {
+        &self.events
+    }
+
+    /// Shared outbound HTTP client
+    pub fn http(&self) -> &HttpClient {
+        &self.http
+    }
+}
+
+/// Routes a plugin contributes
+#[derive(Clone)]
+pub struct PluginRoutes {
+    plugin_id: String,
+    routes: Vec<(PluginRoute, MethodRouter<PluginScope>)>,
+}
+
+impl PluginRoutes {
+    pub fn new(plugin_id: impl Into<String>) -> Self {
+        Self {
+            plugin_id: plugin_id.into(),
+            routes: Vec::new(),
+        }
+    }
+
+    /// Serve `route` with `handler`
+    pub fn route<H, T>(mut self, route: PluginRoute, handler: H) -> Self
+    where
+        H: Handler<T, PluginScope>,
+        T: 'static,
+    {
+        let filter = MethodFilter::try_from(route.method.clone()).unwrap_or(MethodFilter::GET);
+        self.routes.push((route, on(filter, handler)));
+        self
+    }
+
+    pub fn plugin_id(&self) -> &str {
+        &self.plugin_id
+    }
+
+    /// Route definitions, in registration order
+    pub fn definitions(&self) -> impl Iterator<Item = &PluginRoute> {
+        self.routes.iter().map(|(route, _)| route)
+    }
+
+    /// Check the plugin id and route definitions, including routes that
+    /// would match the same requests
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |message: String| Error::Plugin {
+            plugin_id: self.plugin_id.clone(),
+            message,
+        };
+
+        if self.plugin_id.is_empty()
+            || !self
+                .plugin_id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(invalid(
+                "plugin id must be letters, digits, `-` and `_` to be mounted".to_string(),
+            ));
+        }
+
+        let mut seen: BTreeMap<(String, String), &str> = BTreeMap::new();
+        for route in self.definitions() {
+            route.validate().map_err(invalid)?;
+            let key = (route.method.to_string(), route.shape());
+            if let Some(existing) = seen.insert(key, &route.path) {
+                return Err(invalid(format!(
+                    "route {} {} conflicts with {} {}",
+                    route.method, route.path, route.method, existing
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Plugins that serve their own routes
+pub trait RoutablePlugin: Send + Sync {
+    /// Routes to mount under `/plugins/{id}/`
+    fn routes(&self) -> PluginRoutes;
+}
+
+/// Plugin routers mounted by the server
+///
+/// Routes may be registered at any time; requests reach them through
+/// [`PluginRouteRegistry::service`].
+#[derive(Default)]
+pub struct PluginRouteRegistry {
+    plugins: RwLock<BTreeMap<String, PluginRoutes>>,
+    /// Routers built from `plugins`, on the first request to each plugin
+    mounted: RwLock<HashMap<String, Router>>,
+}
+
+impl PluginRouteRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register the routes of `plugin`; refused when they conflict with
+    /// each other or the plugin already registered routes
+    pub fn register(&self, plugin: &dyn RoutablePlugin) -> Result<()> {
+        self.register_routes(plugin.routes())
+    }
+
+    pub fn register_routes(&self, routes: PluginRoutes) -> Result<()> {
+        routes.validate()?;
+
+        let mut plugins = self.plugins.write();
+        if plugins.contains_key(routes.plugin_id()) {
+            return Err(Error::Plugin {
+                plugin_id: routes.plugin_id().to_string(),
+                message: "routes are already registered".to_string(),
+            });
+        }
+        plugins.insert(routes.plugin_id().to_string(), routes);
+        Ok(())
+    }
+
+    /// Plugins with registered routes
+    pub fn plugin_ids(&self) -> Vec<String> {
+        self.plugins.read().keys().cloned().collect()
+    }
+
+    /// Service to nest under [`PLUGIN_ROUTES_PREFIX`], serving every plugin
+    /// registered in `state.plugin_routes` by the time a request arrives
+    ///
+    /// Requests to plugins that are not active get `404 Not Found`.
+    pub fn service(state: AppState) -> Router {
+        Router::new().fallback(dispatch).with_state(state)
+    }
+
+    /// Router of one plugin, nested under its id
+    fn mounted(&self, plugin_id: &str, state: &AppState) -> Option<Router> {
+        if let Some(router) = self.mounted.read().get(plugin_id) {
+            return Some(router.clone());
+        }
+        let plugins = self.plugins.read();
+        let routes = plugins.get(plugin_id)?;
+
+        let mut plugin_router = Router::new();
+        for (route, method_router) in &routes.routes {
+            let guarded = method_router
+                .clone()
+                .route_layer(axum_middleware::from_fn_with_state(
+                    (state.clone(), route.auth.clone()),
+                    require_route_auth,
+                ));
+            plugin_router = plugin_router.route(&route.path, guarded);
+        }
+        let plugin_router = plugin_router
+            .layer(axum_middleware::from_fn_with_state(
+                (state.plugins.clone(), Arc::<str>::from(plugin_id)),
+                require_active_plugin,
+            ))
+            .with_state(PluginScope::new(plugin_id, state));
+        let router = Router::new().nest(&format!("/{}", plugin_id), plugin_router);
+
+        self.mounted
+            .write()
+            .insert(plugin_id.to_string(), router.clone());
+        Some(router)
+    }
+
+    /// OpenAPI paths of all plugin routes, keyed by their full path
+    pub fn openapi_paths(&self) -> serde_json::Map<String, serde_json::Value> {
+        let mut paths: BTreeMap<String, serde_json::Map<String, serde_json::Value>> =
+            BTreeMap::new();
+
+        for (plugin_id, routes) in self.plugins.read().iter() {
+            for route in routes.definitions() {
+                let mut operation = json!({
+                    "summary": route.summary,
+                    "operationId": format!(
+                        "{}_{}_{}",
+                        plugin_id,
+                        route.method.as_str().to_lowercase(),
+                        route
+                            .path
+                            .split('/')
+                            .map(|segment| segment.trim_start_matches([':', '*']).replace('-', "_"))
+                            .filter(|segment| !segment.is_empty())
+                            .collect::<Vec<_>>()
+                            .join("_"),
+                    ),
+                    "tags": [plugin_id],
+                    "parameters": route
+                        .path
+                        .split('/')
+                        .filter_map(|segment| segment.strip_prefix([':', '*']))
+                        .map(|name| json!({
+                            "name": name,
+                            "in": "path",
+                            "required": true,
+                            "schema": { "type": "string" }
+                        }))
+                        .collect::<Vec<_>>(),
+                    "responses": {
+                        "200": { "description": "Success" }
+                    }
+                });
+                match &route.auth {
+                    RouteAuth::Public => {
+                        operation["security"] = json!([]);
+                    }
+                    RouteAuth::Authenticated => {
+                        operation["responses"]["401"] = json!({ "description": "Not signed in" });
+                    }
+                    RouteAuth::Role(role) => {
+                        operation["responses"]["401"] = json!({ "description": "Not signed in" });
+                        operation["responses"]["403"] =
+                            json!({ "description": format!("Requires the {} role", role) });
+                        operation["x-required-role"] = json!(role);
+                    }
+                }
+
+                paths
+                    .entry(format!(
+                        "{}/{}{}",
+                        PLUGIN_ROUTES_PREFIX,
+                        plugin_id,
+                        route.openapi_path()
+                    ))
+                    .or_default()
+                    .insert(route.method.as_str().to_lowercase(), operation);
+            }
+        }
+
+        paths
+            .into_iter()
+            .map(|(path, operations)| (path, operations.into()))
+            .collect()
+    }
+
+    /// OpenAPI document of the API, with the routes plugins mount
+    pub fn openapi_spec(&self) -> serde_json::Value {
+        json!({
+            "openapi": "3.0.0",
+            "info": {
+                "title": "RustPress API",
+                "version": env!("CARGO_PKG_VERSION")
+            },
+            "components": {
+                "securitySchemes": {
+                    "bearerAuth": { "type": "http", "scheme": "bearer", "bearerFormat": "JWT" }
+                }
+            },
+            "security": [{ "bearerAuth": [] }],
+            "paths": self.openapi_paths()
+        })
+    }
+}
+
+/// Hand a request under [`PLUGIN_ROUTES_PREFIX`] to its plugin's router
+async fn dispatch(State(state): State<AppState>, request: Request<Body>) -> Response {
+    let plugin_id = request
+        .uri()
+        .path()
+        .trim_start_matches('/')
+        .split('/')
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    match state.plugin_routes.mounted(&plugin_id, &state) {
+        Some(router) => router.oneshot(request).await.into_response(),
+        None => (StatusCode::NOT_FOUND, "Not found").into_response(),
+    }
+}
+
+/// Enforce the declared [`RouteAuth`] of a plugin route
+///
+/// The signed-in user is passed on as a request extension, so handlers
+/// read it with `Extension<AuthUser>`.
+async fn require_route_auth(
+    State((state, auth)): State<(AppState, RouteAuth)>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let (mut parts, body) = request.into_parts();
+    let user = AuthUser::from_request_parts(&mut parts, &state).await;
+
+    match (&auth, user) {
+        (RouteAuth::Public, Ok(user)) => parts.extensions.insert(user),
+        (RouteAuth::Public, Err(_)) => None,
+        (_, Err(e)) => return e.into_response(),
+        (_, Ok(user)) if !auth.allows(Some(&user)) => {
+            return HttpError::forbidden("Insufficient permissions for this plugin route")
+                .into_response()
+        }
+        (_, Ok(user)) => parts.extensions.insert(user),
+    };
+
+    next.run(Request::from_parts(parts, body)).await
+}
+
+/// Hide the routes of plugins that are not active
+async fn require_active_plugin(
+    State((plugins, plugin_id)): State<(Arc<tokio::sync::RwLock<PluginManager>>, Arc<str>)>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let active = plugins.read().await.state(&plugin_id) == Some(PluginState::Active);
+    if !active {
+        return (StatusCode::NOT_FOUND, "Not found").into_response();
+    }
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn ok() -> &'static str {
+        "ok"
+    }
+
+    #[test]
+    fn test_route_conflicts() {
+        let routes = PluginRoutes::new("gallery")
+            .route(PluginRoute::get("/albums/:id"), ok)
+            .route(PluginRoute::delete("/albums/:id"), ok)
+            .route(PluginRoute::get("/albums/:slug"), ok);
+
+        let err = routes.validate().unwrap_err();
+        assert!(err.to_string().contains("/albums/:slug"));
+
+        let routes = PluginRoutes::new("gallery")
+            .route(PluginRoute::get("/albums/:id"), ok)
+            .route(PluginRoute::get("/albums/:id/photos"), ok)
+            .route(PluginRoute::post("/albums/:id"), ok);
+        assert!(routes.validate().is_ok());
+    }
+
+    #[test]
+    fn test_invalid_routes() {
+        let routes = PluginRoutes::new("gallery").route(PluginRoute::get("albums"), ok);
+        assert!(routes.validate().is_err());
+
+        let routes = PluginRoutes::new("../admin").route(PluginRoute::get("/albums"), ok);
+        assert!(routes.validate().is_err());
+    }
+
+    #[test]
+    fn test_duplicate_plugin_registration() {
+        let registry = PluginRouteRegistry::new();
+        let routes = PluginRoutes::new("gallery").route(PluginRoute::get("/albums").public(), ok);
+
+        registry.register_routes(routes.clone()).unwrap();
+        assert!(registry.register_routes(routes).is_err());
+        assert_eq!(registry.plugin_ids(), vec!["gallery".to_string()]);
+    }
+
+    #[test]
+    fn test_openapi_spec() {
+        let registry = PluginRouteRegistry::new();
+        registry
+            .register_routes(
+                PluginRoutes::new("gallery")
+                    .route(
+                        PluginRoute::get("/albums/:id")
+                            .public()
+                            .summary("Get an album"),
+                        ok,
+                    )
+                    .route(PluginRoute::delete("/albums/:id").role("editor"), ok),
+            )
+            .unwrap();
+
+        let spec = registry.openapi_spec();
+        let path = &spec["paths"]["/plugins/gallery/albums/{id}"];
+
+        assert_eq!(path["get"]["summary"], "Get an album");
+        assert_eq!(path["get"]["security"], json!([]));
+        assert_eq!(path["get"]["parameters"][0]["name"], "id");
+        assert_eq!(path["delete"]["x-required-role"], "editor");
+        assert_eq!(path["delete"]["operationId"], "gallery_delete_albums_id");
+    }
+}
@@ -117,6 +117,33 @@ impl<T: Serialize> IntoResponse for CreatedAt<T> {
     }
 }
 
+/// Response for a versioned entity, exposing its version as an `ETag`
+pub struct Versioned<T: Serialize> {
+    pub data: T,
+    pub version: i64,
+}
+
+impl<T: Serialize> Versioned<T> {
+    pub fn new(data: T, version: i64) -> Self {
+        Self { data, version }
+    }
+
+    /// ETag value for a version
+    pub fn etag(version: i64) -> String {
+        format!("\"{}\"", version)
+    }
+}
+
+impl<T: Serialize> IntoResponse for Versioned<T> {
+    fn into_response(self) -> Response {
+        let mut response = SuccessResponse::new(self.data).into_response();
+        if let Ok(etag) = HeaderValue::from_str(&Self::etag(self.version)) {
+            response.headers_mut().insert(header::ETAG, etag);
+        }
+        response
+    }
+}
+
 /// No content response (204)
 pub struct NoContent;
 
@@ -208,6 +235,48 @@ impl IntoResponse for FileDownload {
 pub struct StreamResponse {
     pub stream: tokio_stream::wrappers::ReceiverStream<Result<bytes::Bytes, std::io::Error>>,
     pub content_type: String,
+    pub filename: Option<String>,
+}
+
+impl StreamResponse {
+    pub fn new(
+        stream: tokio_stream::wrappers::ReceiverStream<Result<bytes::Bytes, std::io::Error>>,
+        content_type: impl Into<String>,
+    ) -> Self {
+        Self {
+            stream,
+            content_type: content_type.into(),
+            filename: None,
+        }
+    }
+
+    /// Serve the stream as an attachment with the given filename
+    pub fn with_filename(mut self, filename: impl Into<String>) -> Self {
+        self.filename = Some(filename.into());
+        self
+    }
+}
+
+impl IntoResponse for StreamResponse {
+    fn into_response(self) -> Response {
+        let mut response = Response::new(axum::body::Body::from_stream(self.stream));
+
+        let headers = response.headers_mut();
+
+        if let Ok(content_type) = HeaderValue::from_str(&self.content_type) {
+            headers.insert(header::CONTENT_TYPE, content_type);
+        }
+        headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+
+        if let Some(filename) = self.filename {
+            let disposition = format!("attachment; filename=\"{}\"", filename);
+            if let Ok(disposition) = HeaderValue::from_str(&disposition) {
+                headers.insert(header::CONTENT_DISPOSITION, disposition);
+            }
+        }
+
+        response
+    }
 }
 
 /// Redirect response
@@ -268,6 +337,11 @@ pub fn created<T: Serialize>(data: T) -> impl IntoResponse {
     Created(data)
 }
 
+/// Versioned JSON response helper (sets `ETag`)
+pub fn versioned<T: Serialize>(data: T, version: i64) -> impl IntoResponse {
+    Versioned::new(data, version)
+}
+
 /// No content response helper
 pub fn no_content() -> impl IntoResponse {
     NoContent
@@ -292,6 +366,12 @@ mod tests {
         assert_eq!(response.meta.total_pages, 10);
     }
 
+    #[test]
+    fn test_versioned_etag() {
+        let response = Versioned::new("test data", 4).into_response();
+        assert_eq!(response.headers().get(header::ETAG).unwrap(), "\"4\"");
+    }
+
     #[test]
     fn test_pagination_meta() {
         // PaginationMeta::new(page, per_page, total)
@@ -1,20 +1,24 @@
 //! Route definitions and router configuration.
 
+pub mod graphql;
+
 use axum::{
     extract::{ConnectInfo, Query, State},
     http::header,
     response::{Html, IntoResponse, Response},
-    routing::{delete, get, post, put},
+    routing::{delete, get, patch, post, put},
     Json, Router,
 };
+use rustpress_core::plugin::PluginState;
 use serde::{Deserialize, Serialize};
 use tower_http::services::{ServeDir, ServeFile};
 use uuid::Uuid;
 
 use crate::error::{HttpError, HttpResult};
-use crate::extract::{AuthUser, PaginatedQuery, PathId, ValidatedJson};
-use crate::response::{created, json, no_content, paginated, SuccessResponse};
+use crate::extract::{AuthUser, IfMatch, PaginatedQuery, PathId, ValidatedJson};
+use crate::response::{created, json, no_content, paginated, versioned, SuccessResponse};
 use crate::state::AppState;
+use crate::transaction::RequestTx;
 use std::sync::Arc;
 
 /// Create the main application router
@@ -26,10 +30,20 @@ pub fn create_router(state: AppState) -> Router {
         .route("/api/health", get(health_check))
         // API v1 routes
         .nest("/api/v1", api_v1_routes())
+        // Read-only public API, authenticated by public API keys
+        .nest(
+            crate::services::public_api::PUBLIC_API_PREFIX,
+            public_api_routes(&state),
+        )
         // Cloudflare plugin routes (separate state)
         .nest_service("/api/v1/cloudflare", build_cloudflare_router(&state))
         // RustBuilder page builder plugin routes
         .nest_service("/api/v1/rustbuilder", build_rustbuilder_router(&state))
+        // Routers contributed by plugins
+        .nest_service(
+            crate::plugin_routes::PLUGIN_ROUTES_PREFIX,
+            crate::plugin_routes::PluginRouteRegistry::service(state.clone()),
+        )
         // RustBuilder visual editor UI
         .nest("/pagebuilder", pagebuilder_routes())
         // Admin UI routes (serve static files, handle by frontend)
@@ -41,11 +55,31 @@ pub fn create_router(state: AppState) -> Router {
         .nest("/admin", admin_routes())
         // Public-facing website routes (theme rendering)
         .merge(public_routes())
+        // Public keys the site signs outbound requests with
+        .route(
+            crate::services::http_signatures::SIGNATURE_DIRECTORY_PATH,
+            get(signature_directory_handler),
+        )
         // Metrics endpoint
         .route("/metrics", get(metrics_handler))
+        // Theme's 404 page
+        .fallback(not_found_handler)
         .with_state(state)
 }
 
+/// Render the theme's 404 page for requests no route matched
+async fn not_found_handler(State(state): State<AppState>, uri: axum::http::Uri) -> Response {
+    if uri.path().starts_with("/api/") {
+        return axum::http::StatusCode::NOT_FOUND.into_response();
+    }
+
+    let mut response = rendered_response(state.renderer().render_404(None).await);
+    if response.status().is_success() {
+        *response.status_mut() = axum::http::StatusCode::NOT_FOUND;
+    }
+    response
+}
+
 /// Admin routes - serve static files from admin-ui directory
 fn admin_routes() -> Router<AppState> {
     // Path to admin UI directory (built files are in ./admin-ui/dist)
@@ -234,23 +268,80 @@ fn public_routes() -> Router<AppState> {
         .route("/post/:slug", get(public_post_handler))
         // Page
         .route("/page/:slug", get(public_page_handler))
+        // Events: single events, upcoming and past archives, iCalendar feed
+        .route("/event/:slug", get(public_event_handler))
+        .route("/events", get(public_events_handler))
+        .route("/events/past", get(public_past_events_handler))
+        .route("/events.ics", get(public_events_ical_handler))
         // Alternative: WordPress-style /:slug for pages
-        // Category archive
+        // Category archive (intersections: /category/a+b, /category/a?tag=b)
         .route("/category/:slug", get(public_category_handler))
+        .route("/category/:slug/feed", get(public_category_feed_handler))
+        .route(
+            "/category/:slug/feed/:format",
+            get(public_category_feed_handler),
+        )
         // Tag archive
         .route("/tag/:slug", get(public_tag_handler))
+        .route("/tag/:slug/feed", get(public_tag_feed_handler))
+        .route("/tag/:slug/feed/:format", get(public_tag_feed_handler))
         // Author archive
         .route("/author/:slug", get(public_author_handler))
+        .route("/author/:slug/feed", get(public_author_feed_handler))
+        .route(
+            "/author/:slug/feed/:format",
+            get(public_author_feed_handler),
+        )
+        // Date archives (/2024/05, /2024/05/14); trailing slashes redirect.
+        // Two-segment paths not starting with a digit are custom taxonomy
+        // term archives (/genre/jazz)
+        .route("/:year/:month", get(public_month_archive_handler))
+        .route("/:year/:month/", get(public_month_archive_handler))
+        .route("/:year/:month/feed", get(public_month_feed_handler))
+        .route("/:year/:month/feed/:format", get(public_month_feed_handler))
+        .route("/:year/:month/:day", get(public_day_archive_handler))
+        .route("/:year/:month/:day/", get(public_day_archive_handler))
+        .route("/:year/:month/:day/feed", get(public_day_feed_handler))
+        .route(
+            "/:year/:month/:day/feed/:format",
+            get(public_day_feed_handler),
+        )
         // Search results
         .route("/search", get(public_search_handler))
-        // Feed
+        // Site feed (RSS at /feed and /feed/rss, /feed/atom, /feed/json)
         .route("/feed", get(public_feed_handler))
-        .route("/feed/rss", get(public_feed_handler))
-        .route("/feed/atom", get(public_atom_feed_handler))
+        .route("/feed/:format", get(public_feed_handler))
         // Sitemap
         .route("/sitemap.xml", get(public_sitemap_handler))
         // Robots.txt
         .route("/robots.txt", get(public_robots_handler))
+        // IndexNow key file
+        .route(INDEXNOW_KEY_PATH, get(indexnow_key_handler))
+        // Core Web Vitals beacons
+        .route(BEACON_PATH, post(web_vitals_beacon_handler))
+        // Resized versions of media library images, made on first request
+        .route("/media/transform/:id/:size", get(media_transform_handler))
+        // Inbound email forwarded by Mailgun routes and SES through SNS
+        .route(
+            "/inbound-email/mailgun/:token/mime",
+            post(mailgun_inbound_email_handler).layer(axum::extract::DefaultBodyLimit::max(
+                INBOUND_EMAIL_BODY_LIMIT,
+            )),
+        )
+        .route(
+            "/inbound-email/ses/:token",
+            post(ses_inbound_email_handler).layer(axum::extract::DefaultBodyLimit::max(
+                INBOUND_EMAIL_BODY_LIMIT,
+            )),
+        )
+        // Gravatar proxy
+        .route("/avatar/:id", get(avatar_proxy_handler))
+        // Generated Open Graph images; `/social-card` is the old path
+        .route("/og-image/:id", get(og_image_handler))
+        .route("/social-card/:id", get(og_image_handler))
+        // Podcast feed and episode audio
+        .route("/podcast/feed", get(podcast_feed_handler))
+        .route("/podcast/audio/:file", get(podcast_audio_handler))
         // Theme assets
         .route("/themes/:theme_id/*path", get(theme_asset_handler))
 }
@@ -348,6 +439,8 @@ fn chat_routes() -> Router<AppState> {
 /// API v1 routes
 fn api_v1_routes() -> Router<AppState> {
     Router::new()
+        // OpenAPI document
+        .route("/openapi.json", get(openapi_handler))
         // WebSocket endpoint for real-time collaboration
         .route("/ws", get(crate::websocket::websocket_handler))
         // Chat routes
@@ -360,10 +453,14 @@ fn api_v1_routes() -> Router<AppState> {
         .nest("/auth", auth_routes())
         // User routes
         .nest("/users", user_routes())
+        // User groups, their members and owned sections
+        .nest("/groups", group_routes())
         // Post routes
         .nest("/posts", post_routes())
         // Page routes
         .nest("/pages", page_routes())
+        // Redirect rules for moved and removed content
+        .nest("/redirects", redirect_routes())
         // Media routes
         .nest("/media", media_routes())
         // Comment routes
@@ -374,8 +471,12 @@ fn api_v1_routes() -> Router<AppState> {
         .nest("/storage", storage_routes())
         // Plugin routes
         .nest("/plugins", plugin_routes())
+        // WebAssembly plugins installed at runtime
+        .nest("/wasm-plugins", wasm_plugin_routes())
         // Theme routes
         .nest("/themes", theme_routes())
+        // Theme and plugin compatibility audit
+        .route("/compat", get(compat_audit_handler))
         // Search routes
         .nest("/search", search_routes())
         // Backup routes
@@ -386,8 +487,10 @@ fn api_v1_routes() -> Router<AppState> {
         .nest("/cache", cache_routes())
         // CDN routes
         .nest("/cdn", cdn_routes())
-        // Taxonomy routes (categories, tags)
+        // Taxonomy registry with its terms, and the legacy categories and tags
         .nest("/taxonomies", taxonomy_routes())
+        // Terms: meta, merge, split and bulk re-assignment
+        .nest("/terms", term_routes())
         // Direct category/tag routes (aliases for frontend compatibility)
         .route(
             "/categories",
@@ -414,6 +517,60 @@ fn api_v1_routes() -> Router<AppState> {
         .nest("/stats", stats_routes())
         // Email routes
         .nest("/email", email_routes())
+        // Streaming export routes
+        .nest("/exports", export_routes())
+        // WordPress (WXR) imports
+        .nest("/import", import_routes())
+        // Anti-abuse challenges for public forms
+        .nest("/challenges", challenge_routes())
+        // CAPTCHA provider settings and widget configuration
+        .nest("/captcha", captcha_routes())
+        // Social accounts, preview card design and the share queue
+        .nest("/social", social_routes())
+        // Podcast show settings, episodes and download statistics
+        .nest("/podcast", podcast_routes())
+        // Event listing and calendar views
+        .nest("/events", event_routes())
+        .nest("/geoip", geoip_routes())
+        // Per-region cookie banner, age gate and content blocking rules
+        .nest("/compliance", compliance_routes())
+        // Profile field definitions and public author profiles
+        .nest("/profile-fields", profile_field_routes())
+        .route("/authors/:slug", get(get_author_profile_handler))
+        // Saved filter/sort/column views for admin lists
+        .nest("/saved-views", saved_view_routes())
+        // Public API keys and usage analytics
+        .nest("/public-api", public_api_admin_routes())
+        // HTTP message signature keys for server-to-server integrations
+        .nest("/http-signatures", http_signature_routes())
+        // Outbound webhook endpoints, delivery logs and redelivery
+        .nest("/webhooks", webhook_routes())
+        // Mailboxes whose messages become draft posts, and their message logs
+        .nest("/inbound-email", inbound_email_routes())
+        // Per-role HTML sanitization policy, reports and legacy sweeps
+        .nest("/sanitization", sanitization_routes())
+        // Typography, emoji and shortlink content filter settings
+        .nest("/content-filters", content_filter_routes())
+        // Theme and plugin allowlists for the network, tenants and sites
+        .nest("/network/allowlists", network_allowlist_routes())
+        // Sites of the network, their status and plugins
+        .nest("/network/sites", network_site_routes())
+        // Usage of every site in a month, for billing
+        .route("/network/usage", get(network_usage_handler))
+        // Quotas and usage of the site being served
+        .route("/usage", get(current_site_usage_handler))
+        // Read-only maintenance mode
+        .nest("/maintenance", maintenance_routes())
+        // Dead-letter queue of failed background jobs
+        .nest("/jobs", job_routes())
+        // Latency and error injection for resilience testing
+        .nest("/faults", fault_routes())
+        // Outbound HTTP policies and per-destination metrics
+        .route("/outbound-http", get(outbound_http_handler))
+        // Live dashboard notifications
+        .nest("/live", crate::ws::routes())
+        // GraphQL API
+        .nest("/graphql", graphql::routes())
 }
 
 /// Theme management routes
@@ -429,6 +586,11 @@ fn theme_routes() -> Router<AppState> {
         .route("/available", get(get_available_themes_handler))
         // Get active theme
         .route("/active", get(get_active_theme_handler))
+        // Classic-to-block render migration assist
+        .route(
+            "/render-migration",
+            get(get_render_migration_handler).put(update_render_migration_handler),
+        )
         // Get/update specific theme
         .route(
             "/:theme_id",
@@ -465,10 +627,32 @@ fn auth_routes() -> Router<AppState> {
         .route("/login", post(login_handler))
         .route("/logout", post(logout_handler))
         .route("/refresh", post(refresh_token_handler))
+        // OAuth2 device authorization grant for the CLI
+        .route("/device/code", post(device_code_handler))
+        .route("/device/token", post(device_token_handler))
+        .route(
+            "/device",
+            get(get_device_authorization_handler).post(resolve_device_authorization_handler),
+        )
+        // SAML single sign-on with the configured identity providers
+        .route("/saml/metadata", get(saml_metadata_handler))
+        .route("/saml/:provider/login", get(saml_login_handler))
+        .route("/saml/acs", post(saml_acs_handler))
+        .route(
+            "/saml/config",
+            get(get_saml_config_handler).put(update_saml_config_handler),
+        )
         .route("/register", post(register_handler))
         .route("/forgot-password", post(forgot_password_handler))
         .route("/reset-password", post(reset_password_handler))
         .route("/me", get(current_user_handler))
+        // Scoped API keys and what the caller may do with its credentials
+        .route("/capabilities", get(capabilities_handler))
+        .route(
+            "/api-keys",
+            get(list_user_api_keys_handler).post(create_user_api_key_handler),
+        )
+        .route("/api-keys/:id", delete(revoke_user_api_key_handler))
 }
 
 /// User management routes
@@ -483,6 +667,20 @@ fn user_routes() -> Router<AppState> {
                 .delete(delete_user_handler),
         )
         .route("/:id/roles", put(update_user_roles_handler))
+        .route(
+            "/:id/profile",
+            get(get_user_profile_handler).put(update_user_profile_handler),
+        )
+        .route(
+            "/:id/avatar",
+            post(upload_avatar_handler).delete(delete_avatar_handler),
+        )
+        .route("/export", get(export_users_handler))
+        .route(
+            "/imports",
+            get(list_user_imports_handler).post(start_user_import_handler),
+        )
+        .route("/imports/:id", get(get_user_import_handler))
 }
 
 /// Post routes
@@ -490,6 +688,8 @@ fn post_routes() -> Router<AppState> {
     Router::new()
         .route("/", get(list_posts_handler).post(create_post_handler))
         .route("/bulk-delete", post(bulk_delete_posts_handler))
+        // Editorial calendar, and moving posts between its days
+        .route("/calendar", get(post_calendar_handler))
         .route(
             "/:id",
             get(get_post_handler)
@@ -498,7 +698,56 @@ fn post_routes() -> Router<AppState> {
         )
         .route("/:id/publish", post(publish_post_handler))
         .route("/:id/unpublish", post(unpublish_post_handler))
+        .route("/:id/schedule", patch(reschedule_post_handler))
         .route("/:id/duplicate", post(duplicate_post_handler))
+        .route("/:id/comments", get(post_comments_handler))
+        .route(
+            "/:id/comment-settings",
+            get(get_post_comment_settings_handler).put(update_post_comment_settings_handler),
+        )
+        // Internal editorial discussions, separate from public comments
+        .route(
+            "/:id/discussions",
+            get(list_discussions_handler).post(open_discussion_handler),
+        )
+        .route(
+            "/discussions/:id",
+            get(get_discussion_handler).delete(delete_discussion_handler),
+        )
+        .route("/discussions/:id/replies", post(reply_discussion_handler))
+        .route("/discussions/:id/resolve", post(resolve_discussion_handler))
+        .route("/discussions/:id/reopen", post(reopen_discussion_handler))
+        .route(
+            "/discussions/comments/:id",
+            put(edit_discussion_comment_handler).delete(delete_discussion_comment_handler),
+        )
+        // Share previews and sharing to social networks
+        .route(
+            "/:id/social",
+            get(get_post_social_handler).put(update_post_social_handler),
+        )
+        .route("/:id/social/card", get(preview_social_card_handler))
+        .route("/:id/social/shares", post(share_post_handler))
+        // Podcast episode enclosure and downloads
+        .route(
+            "/:id/episode",
+            get(get_post_episode_handler)
+                .put(update_post_episode_handler)
+                .delete(delete_post_episode_handler),
+        )
+        .route("/:id/episode/stats", get(post_episode_stats_handler))
+        // Event schedule, venue and tickets
+        .route(
+            "/:id/event",
+            get(get_post_event_handler)
+                .put(update_post_event_handler)
+                .delete(delete_post_event_handler),
+        )
+        // Editing a post together, and the autosaves taken meanwhile
+        .route("/:id/collaborate", get(crate::collab::collaborate_handler))
+        .route("/:id/collaboration", get(collaboration_status_handler))
+        .route("/:id/autosaves", get(list_autosaves_handler))
+        .route("/autosaves/:id", get(get_autosave_handler))
 }
 
 /// Page routes
@@ -521,12 +770,30 @@ fn media_routes() -> Router<AppState> {
             "/folders",
             get(list_media_folders_handler).post(create_media_folder_handler),
         )
+        .route(
+            "/folders/:id",
+            get(get_media_folder_handler)
+                .put(update_media_folder_handler)
+                .delete(delete_media_folder_handler),
+        )
+        .route("/bulk/move", post(bulk_move_media_handler))
+        .route("/bulk/delete", post(bulk_delete_media_handler))
+        .route("/bulk/tag", post(bulk_tag_media_handler))
+        .route(
+            "/responsive-images/settings",
+            get(get_responsive_images_config_handler).put(update_responsive_images_config_handler),
+        )
         .route(
             "/:id",
             get(get_media_handler)
                 .put(update_media_handler)
                 .delete(delete_media_handler),
         )
+        .route("/:id/usage", get(media_usage_handler))
+        .route(
+            "/:id/sizes",
+            get(media_sizes_handler).delete(purge_media_sizes_handler),
+        )
 }
 
 /// Comment routes
@@ -535,6 +802,7 @@ fn comment_routes() -> Router<AppState> {
         .route("/", get(list_comments_handler).post(create_comment_handler))
         .route("/batch", post(batch_moderate_comments_handler))
         .route("/counts", get(comment_counts_handler))
+        .route("/queue", get(comment_queue_handler))
         .route(
             "/:id",
             get(get_comment_handler)
@@ -559,6 +827,27 @@ fn settings_routes() -> Router<AppState> {
         .route("/writing", get(get_writing_settings_handler))
         .route("/discussion", get(get_discussion_settings_handler))
         .route("/permalinks", get(get_permalinks_settings_handler))
+        // Changes to sensitive settings waiting for a second administrator
+        .route(
+            "/change-requests",
+            get(list_setting_change_requests_handler),
+        )
+        .route(
+            "/change-requests/:id",
+            get(get_setting_change_request_handler),
+        )
+        .route(
+            "/change-requests/:id/approve",
+            post(approve_setting_change_request_handler),
+        )
+        .route(
+            "/change-requests/:id/reject",
+            post(reject_setting_change_request_handler),
+        )
+        .route(
+            "/change-requests/:id/cancel",
+            post(cancel_setting_change_request_handler),
+        )
         .route(
             "/:key",
             get(get_setting_handler).put(update_setting_handler),
@@ -591,6 +880,40 @@ fn plugin_routes() -> Router<AppState> {
         )
         .route("/:id/activate", post(activate_plugin_handler))
         .route("/:id/deactivate", post(deactivate_plugin_handler))
+        .route(
+            "/:id/settings",
+            get(get_plugin_settings_handler).put(update_plugin_settings_handler),
+        )
+}
+
+#[cfg(feature = "wasm-plugins")]
+fn wasm_plugin_routes() -> Router<AppState> {
+    crate::wasm_plugins::routes()
+}
+
+/// This build has no WebAssembly plugin host (feature 'wasm-plugins')
+#[cfg(not(feature = "wasm-plugins"))]
+fn wasm_plugin_routes() -> Router<AppState> {
+    Router::new()
+}
+
+/// Audit every installed plugin and theme against the platform and each
+/// other
+async fn compat_audit_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can audit compatibility",
+        ));
+    }
+
+    let plugins = state.plugins.read().await;
+    Ok(json(crate::services::compat::audit(
+        &plugins,
+        state.theme_manager(),
+    )))
 }
 
 // =============================================================================
@@ -601,12 +924,15 @@ fn plugin_routes() -> Router<AppState> {
 struct HealthResponse {
     status: String,
     version: String,
+    read_only: bool,
 }
 
-async fn health_check() -> impl axum::response::IntoResponse {
+async fn health_check(State(state): State<AppState>) -> impl axum::response::IntoResponse {
+    let read_only = state.read_only.is_enabled();
     Json(HealthResponse {
-        status: "healthy".to_string(),
+        status: if read_only { "read_only" } else { "healthy" }.to_string(),
         version: env!("CARGO_PKG_VERSION").to_string(),
+        read_only,
     })
 }
 
@@ -619,7 +945,19 @@ async fn readiness_check(State(state): State<AppState>) -> axum::response::Respo
     let db_healthy = state.db().is_connected().await;
 
     if db_healthy {
-        Json(serde_json::json!({ "status": "ready" })).into_response()
+        Json(serde_json::json!({
+            "status": "ready",
+            "read_only": state.read_only.status(),
+        }))
+        .into_response()
+    } else if state.read_only.is_enabled() {
+        // Reads are still served from caches while the primary fails over
+        Json(serde_json::json!({
+            "status": "ready",
+            "reason": "database unavailable",
+            "read_only": state.read_only.status(),
+        }))
+        .into_response()
     } else {
         (
             axum::http::StatusCode::SERVICE_UNAVAILABLE,
@@ -1338,8 +1676,14 @@ struct RegisterRequest {
 
 async fn register_handler(
     State(state): State<AppState>,
-    Json(payload): Json<RegisterRequest>,
+    Json(body): Json<serde_json::Value>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
+    let payload: RegisterRequest = challenged_body(
+        &state,
+        crate::services::abuse_challenge::REGISTRATION_ENDPOINT,
+        body,
+    )
+    .await?;
     let pool = state.db().inner();
 
     // Validate password
@@ -1424,8 +1768,14 @@ struct ForgotPasswordRequest {
 
 async fn forgot_password_handler(
     State(state): State<AppState>,
-    Json(payload): Json<ForgotPasswordRequest>,
+    Json(body): Json<serde_json::Value>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
+    let payload: ForgotPasswordRequest = challenged_body(
+        &state,
+        crate::services::abuse_challenge::PASSWORD_RESET_ENDPOINT,
+        body,
+    )
+    .await?;
     let pool = state.db().inner();
 
     // Check if user exists (but don't reveal this to the client)
@@ -1497,10 +1847,11 @@ struct ResetPasswordRequest {
 }
 
 async fn reset_password_handler(
-    State(state): State<AppState>,
+    tx: RequestTx,
     Json(payload): Json<ResetPasswordRequest>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
+    // The password, the token and the sessions change together or not at all
+    let mut conn = tx.conn().await?;
 
     // Validate password
     let validator = PasswordValidator::new(PasswordRules::default());
@@ -1525,7 +1876,7 @@ async fn reset_password_handler(
         "#,
     )
     .bind(&token_hash)
-    .fetch_optional(pool)
+    .fetch_optional(&mut *conn)
     .await
     .map_err(|e| rustpress_core::error::Error::database_with_source("Database error", e))?;
 
@@ -1541,21 +1892,22 @@ async fn reset_password_handler(
     // Update the user's password
     sqlx::query("UPDATE users SET password_hash = $1, updated_at = NOW() WHERE id = $2")
         .bind(&password_hash)
-        .execute(pool)
+        .bind(user_id)
+        .execute(&mut *conn)
         .await
         .map_err(|e| rustpress_core::error::Error::database_with_source("Database error", e))?;
 
     // Mark the token as used
     sqlx::query("UPDATE password_reset_tokens SET used_at = NOW() WHERE id = $1")
         .bind(token_id)
-        .execute(pool)
+        .execute(&mut *conn)
         .await
         .map_err(|e| rustpress_core::error::Error::database_with_source("Database error", e))?;
 
     // Invalidate all sessions for this user (force re-login)
     sqlx::query("UPDATE sessions SET revoked_at = NOW() WHERE user_id = $1 AND revoked_at IS NULL")
         .bind(user_id)
-        .execute(pool)
+        .execute(&mut *conn)
         .await
         .map_err(|e| rustpress_core::error::Error::database_with_source("Database error", e))?;
 
@@ -1624,6 +1976,7 @@ struct UserListQuery {
     search: Option<String>,
     sort_by: Option<String>,
     sort_order: Option<String>,
+    filter: Option<String>,
 }
 
 async fn list_users_handler(
@@ -1641,6 +1994,7 @@ async fn list_users_handler(
         search: query.search,
         sort_by: query.sort_by,
         sort_order: query.sort_order,
+        filter: query.filter,
     };
 
     let result = service.list_users(params).await?;
@@ -1719,5785 +2073,12363 @@ async fn update_user_roles_handler(
 }
 
 // =============================================================================
-// Post Handlers
+// Profile Routes and Handlers
 // =============================================================================
 
-use rustpress_api::services::post_service::{
-    CreatePostRequest, PostListParams, PostService, UpdatePostRequest,
-};
+use crate::extract::MaybeAuthUser;
+use crate::services::{ProfileFieldsConfig, ProfileUpdate, ProfileViewer, ResolvedAvatar};
 
-/// Post list query parameters
-#[derive(Debug, serde::Deserialize)]
-struct PostListQuery {
-    page: Option<u32>,
-    per_page: Option<u32>,
-    status: Option<String>,
-    author_id: Option<Uuid>,
-    search: Option<String>,
-    sort_by: Option<String>,
-    sort_order: Option<String>,
+/// Profile field definition routes
+fn profile_field_routes() -> Router<AppState> {
+    Router::new().route(
+        "/",
+        get(get_profile_fields_handler).put(update_profile_fields_handler),
+    )
 }
 
-async fn list_posts_handler(
-    Query(query): Query<PostListQuery>,
+/// Registered profile fields
+async fn get_profile_fields_handler(
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let service = PostService::new(state.db().inner().clone());
-
-    let params = PostListParams {
-        page: query.page,
-        per_page: query.per_page,
-        status: query.status,
-        author_id: query.author_id,
-        search: query.search,
-        sort_by: query.sort_by,
-        sort_order: query.sort_order,
-    };
-
-    let result = service.list_posts(params).await?;
-    Ok(json(result))
+    Ok(json(state.profiles.config().await.as_ref().clone()))
 }
 
-async fn create_post_handler(
+/// Replace the profile field definitions
+async fn update_profile_fields_handler(
     user: AuthUser,
     State(state): State<AppState>,
-    Json(payload): Json<CreatePostRequest>,
+    Json(config): Json<ProfileFieldsConfig>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let service = PostService::new(state.db().inner().clone());
-    let post = service.create_post(payload, user.id).await?;
-    Ok(created(post))
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can change profile fields",
+        ));
+    }
+
+    state.profiles.set_config(config.clone()).await?;
+    state.avatars.clear();
+    Ok(json(config))
 }
 
-async fn get_post_handler(
+/// A user's profile, filtered by field visibility
+async fn get_user_profile_handler(
+    MaybeAuthUser(user): MaybeAuthUser,
     PathId(id): PathId,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let service = PostService::new(state.db().inner().clone());
-
-    match service.get_post(id).await? {
-        Some(post) => Ok(json(post)),
-        None => Err(rustpress_core::error::Error::not_found("Post", id.to_string()).into()),
+    let viewer = ProfileViewer::for_user(user.map(|u| (u.id, u.is_admin())), id);
+    match state.profiles.view(id, viewer).await? {
+        Some(profile) => Ok(json(profile)),
+        None => Err(rustpress_core::error::Error::not_found("User", id.to_string()).into()),
     }
 }
 
-async fn update_post_handler(
+/// Update profile fields, visibility and avatar (the user or an administrator)
+async fn update_user_profile_handler(
     user: AuthUser,
     PathId(id): PathId,
     State(state): State<AppState>,
-    Json(payload): Json<UpdatePostRequest>,
+    Json(update): Json<ProfileUpdate>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let service = PostService::new(state.db().inner().clone());
-    let post = service.update_post(id, payload).await?;
-    Ok(json(post))
+    if user.id != id && !user.is_admin() {
+        return Err(HttpError::forbidden("You can only edit your own profile"));
+    }
+
+    let profile = state.profiles.update(id, update).await?;
+    state.avatars.invalidate(id);
+    Ok(json(profile))
 }
 
-async fn delete_post_handler(
+/// Upload a new avatar image (multipart field `file`)
+async fn upload_avatar_handler(
     user: AuthUser,
     PathId(id): PathId,
     State(state): State<AppState>,
+    mut multipart: axum::extract::Multipart,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let service = PostService::new(state.db().inner().clone());
-    service.delete_post(id).await?;
-    Ok(no_content())
+    if user.id != id && !user.is_admin() {
+        return Err(HttpError::forbidden("You can only change your own avatar"));
+    }
+
+    let mut data = None;
+    while let Some(field) = multipart.next_field().await.map_err(|e| {
+        rustpress_core::error::Error::validation(format!("Failed to read multipart: {}", e))
+    })? {
+        if field.name() == Some("file") {
+            data = Some(field.bytes().await.map_err(|e| {
+                rustpress_core::error::Error::validation(format!("Failed to read file: {}", e))
+            })?);
+        }
+    }
+    let data = data.ok_or_else(|| rustpress_core::error::Error::validation("No file uploaded"))?;
+
+    Ok(json(state.avatars.upload(id, data).await?))
 }
 
-async fn publish_post_handler(
+/// Remove the avatar, falling back to generated initials
+async fn delete_avatar_handler(
     user: AuthUser,
     PathId(id): PathId,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let service = PostService::new(state.db().inner().clone());
-    let post = service.publish_post(id).await?;
-    Ok(json(post))
+    if user.id != id && !user.is_admin() {
+        return Err(HttpError::forbidden("You can only change your own avatar"));
+    }
+
+    Ok(json(state.avatars.remove(id).await?))
 }
 
-async fn unpublish_post_handler(
-    user: AuthUser,
-    PathId(id): PathId,
+/// Public author profile by username
+async fn get_author_profile_handler(
+    MaybeAuthUser(user): MaybeAuthUser,
+    axum::extract::Path(slug): axum::extract::Path<String>,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let service = PostService::new(state.db().inner().clone());
-    let post = service.unpublish_post(id).await?;
-    Ok(json(post))
+    let viewer = match user {
+        Some(_) => ProfileViewer::Member,
+        None => ProfileViewer::Anonymous,
+    };
+    match state.profiles.view_by_username(&slug, viewer).await? {
+        Some(profile) => Ok(json(profile)),
+        None => Err(rustpress_core::error::Error::not_found("Author", slug).into()),
+    }
 }
 
-/// Bulk delete posts request
-#[derive(Debug, serde::Deserialize)]
-struct BulkDeletePostsRequest {
-    ids: Vec<Uuid>,
+/// Avatar proxy query parameters
+#[derive(Debug, Deserialize)]
+struct AvatarQuery {
+    s: Option<u32>,
 }
 
-/// Bulk delete posts response
-#[derive(Debug, serde::Serialize)]
-struct BulkDeletePostsResponse {
-    deleted: usize,
+/// Serve a user's avatar; Gravatars are proxied so visitors never see
+/// the email hash
+async fn avatar_proxy_handler(
+    PathId(id): PathId,
+    Query(query): Query<AvatarQuery>,
+    State(state): State<AppState>,
+) -> Response {
+    let size = query
+        .s
+        .unwrap_or(crate::services::avatar::DEFAULT_AVATAR_SIZE);
+    match state.avatars.resolve(id, size).await {
+        Ok(Some(ResolvedAvatar::Image(image))) => (
+            [
+                (header::CONTENT_TYPE, image.content_type),
+                (header::CACHE_CONTROL, "public, max-age=3600".to_string()),
+            ],
+            image.bytes,
+        )
+            .into_response(),
+        Ok(Some(ResolvedAvatar::Redirect(url))) => {
+            axum::response::Redirect::temporary(&url).into_response()
+        }
+        Ok(None) => axum::http::StatusCode::NOT_FOUND.into_response(),
+        Err(e) => {
+            tracing::warn!(user_id = %id, "Avatar lookup failed: {}", e);
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
 }
 
-async fn bulk_delete_posts_handler(
+use crate::services::UserImportRequest;
+
+/// Export users without credentials, in the format `users import` reads
+async fn export_users_handler(
     user: AuthUser,
+    Query(params): Query<ExportParams>,
     State(state): State<AppState>,
-    Json(payload): Json<BulkDeletePostsRequest>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let service = PostService::new(state.db().inner().clone());
+    export_dataset_handler(
+        user,
+        axum::extract::Path("users".to_string()),
+        Query(params),
+        State(state),
+    )
+    .await
+}
 
-    let mut deleted_count = 0;
-    for id in payload.ids {
-        if service.delete_post(id).await? {
-            deleted_count += 1;
-        }
+/// Start a bulk user import; poll the returned run for progress
+async fn start_user_import_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(payload): Json<UserImportRequest>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden("Only administrators can import users"));
     }
 
-    Ok(json(BulkDeletePostsResponse {
-        deleted: deleted_count,
-    }))
+    let run = state.user_imports.start(user.id, payload).await?;
+    tracing::info!(
+        import_id = %run.id,
+        user_id = %user.id,
+        total = run.total,
+        dry_run = run.dry_run,
+        "User import started"
+    );
+    state
+        .publish(user_event(
+            Some(&user),
+            "user.import_started",
+            serde_json::json!({
+                "import_id": run.id,
+                "total": run.total,
+                "dry_run": run.dry_run,
+            }),
+        ))
+        .await;
+    Ok(created(run))
 }
 
-async fn duplicate_post_handler(
+/// Recent user imports
+async fn list_user_imports_handler(
     user: AuthUser,
-    PathId(id): PathId,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let service = PostService::new(state.db().inner().clone());
+    if !user.is_admin() {
+        return Err(HttpError::forbidden("Only administrators can view imports"));
+    }
 
-    // Get the original post
-    let original =
-        service
-            .get_post(id)
-            .await?
-            .ok_or_else(|| rustpress_core::error::Error::NotFound {
-                entity_type: "post".to_string(),
-                id: id.to_string(),
-            })?;
+    Ok(json(state.user_imports.list(20).await?))
+}
 
-    // Create a duplicate with modified title
-    let duplicate_request = CreatePostRequest {
-        title: format!("{} (Copy)", original.title),
-        slug: None, // Let the service generate a new slug
-        content: original.content,
-        content_format: original.content_format,
-        excerpt: original.excerpt,
-        status: Some("draft".to_string()), // Always create as draft
-        visibility: original.visibility,
-        password: None,
-        featured_image_id: original.featured_image_id,
-        comment_status: original.comment_status,
-        ping_status: original.ping_status,
-        published_at: None,
-        category_ids: if original.categories.is_empty() {
-            None
-        } else {
-            Some(original.categories.iter().map(|c| c.id).collect())
-        },
-        tag_ids: if original.tags.is_empty() {
-            None
-        } else {
-            Some(original.tags.iter().map(|t| t.id).collect())
-        },
-    };
+/// One user import and its progress
+async fn get_user_import_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden("Only administrators can view imports"));
+    }
 
-    let new_post = service.create_post(duplicate_request, user.id).await?;
-    Ok(created(new_post))
+    Ok(json(state.user_imports.get(id).await?))
 }
 
 // =============================================================================
-// Page Handlers
+// Saved View Routes and Handlers
 // =============================================================================
 
-use rustpress_api::services::page_service::{
-    CreatePageRequest, PageListParams, PageService, UpdatePageRequest,
-};
+use crate::services::{ListResource, SavedViewInput, SavedViewService, ViewUser};
 
-/// Page list query parameters
-#[derive(Debug, serde::Deserialize)]
-struct PageListQuery {
-    page: Option<u32>,
-    per_page: Option<u32>,
-    status: Option<String>,
-    parent_id: Option<Uuid>,
-    search: Option<String>,
-    sort_by: Option<String>,
-    sort_order: Option<String>,
-    hierarchical: Option<bool>,
+/// Saved filter/sort/column views for admin lists
+fn saved_view_routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/",
+            get(list_saved_views_handler).post(create_saved_view_handler),
+        )
+        .route("/order", put(reorder_saved_views_handler))
+        .route("/default", put(set_default_saved_view_handler))
+        .route(
+            "/:id",
+            get(get_saved_view_handler)
+                .put(update_saved_view_handler)
+                .delete(delete_saved_view_handler),
+        )
 }
 
-async fn list_pages_handler(
-    Query(query): Query<PageListQuery>,
-    State(state): State<AppState>,
-) -> HttpResult<impl axum::response::IntoResponse> {
-    let service = PageService::new(state.db().inner().clone());
+fn view_user(user: &AuthUser) -> ViewUser {
+    ViewUser::new(user.id, user.roles.clone())
+}
 
-    let params = PageListParams {
-        page: query.page,
-        per_page: query.per_page,
-        status: query.status,
-        parent_id: query.parent_id,
-        search: query.search,
-        sort_by: query.sort_by,
-        sort_order: query.sort_order,
-        hierarchical: query.hierarchical,
-    };
+/// Saved view list query parameters
+#[derive(Debug, Deserialize)]
+struct SavedViewListQuery {
+    resource: String,
+}
 
-    let result = service.list_pages(params).await?;
-    Ok(json(result))
+/// New saved view for one list
+#[derive(Debug, Deserialize)]
+struct CreateSavedViewRequest {
+    resource: ListResource,
+    #[serde(flatten)]
+    view: SavedViewInput,
 }
 
-async fn create_page_handler(
+/// New view order for one list
+#[derive(Debug, Deserialize)]
+struct ReorderSavedViewsRequest {
+    resource: ListResource,
+    view_ids: Vec<Uuid>,
+}
+
+/// Default view for one list; `null` clears it
+#[derive(Debug, Deserialize)]
+struct DefaultSavedViewRequest {
+    resource: ListResource,
+    view_id: Option<Uuid>,
+}
+
+/// Views the user saved or that were shared with their roles
+async fn list_saved_views_handler(
     user: AuthUser,
+    Query(query): Query<SavedViewListQuery>,
     State(state): State<AppState>,
-    Json(payload): Json<CreatePageRequest>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let service = PageService::new(state.db().inner().clone());
-    let page = service.create_page(payload, user.id).await?;
-    Ok(created(page))
+    let resource: ListResource = query.resource.parse()?;
+    let service = SavedViewService::new(state.db().inner().clone());
+    Ok(json(service.list(&view_user(&user), resource).await?))
 }
 
-async fn get_page_handler(
-    PathId(id): PathId,
+async fn create_saved_view_handler(
+    user: AuthUser,
     State(state): State<AppState>,
+    Json(payload): Json<CreateSavedViewRequest>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let service = PageService::new(state.db().inner().clone());
+    let service = SavedViewService::new(state.db().inner().clone());
+    let view = service
+        .create(&view_user(&user), payload.resource, payload.view)
+        .await?;
+    Ok(created(view))
+}
 
-    match service.get_page(id).await? {
-        Some(page) => Ok(json(page)),
-        None => Err(rustpress_core::error::Error::not_found("Page", id.to_string()).into()),
-    }
+async fn get_saved_view_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let service = SavedViewService::new(state.db().inner().clone());
+    Ok(json(service.get(&view_user(&user), id).await?))
 }
 
-async fn update_page_handler(
+/// Replace a view (its owner or an administrator)
+async fn update_saved_view_handler(
     user: AuthUser,
     PathId(id): PathId,
     State(state): State<AppState>,
-    Json(payload): Json<UpdatePageRequest>,
+    Json(payload): Json<SavedViewInput>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let service = PageService::new(state.db().inner().clone());
-    let page = service.update_page(id, payload).await?;
-    Ok(json(page))
+    let service = SavedViewService::new(state.db().inner().clone());
+    Ok(json(service.update(&view_user(&user), id, payload).await?))
 }
 
-async fn delete_page_handler(
+/// Delete a view (its owner or an administrator)
+async fn delete_saved_view_handler(
     user: AuthUser,
     PathId(id): PathId,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let service = PageService::new(state.db().inner().clone());
-    service.delete_page(id).await?;
+    let service = SavedViewService::new(state.db().inner().clone());
+    service.delete(&view_user(&user), id).await?;
     Ok(no_content())
 }
 
+async fn reorder_saved_views_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(payload): Json<ReorderSavedViewsRequest>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let service = SavedViewService::new(state.db().inner().clone());
+    let views = service
+        .reorder(&view_user(&user), payload.resource, payload.view_ids)
+        .await?;
+    Ok(json(views))
+}
+
+async fn set_default_saved_view_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(payload): Json<DefaultSavedViewRequest>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let service = SavedViewService::new(state.db().inner().clone());
+    let views = service
+        .set_default(&view_user(&user), payload.resource, payload.view_id)
+        .await?;
+    Ok(json(views))
+}
+
 // =============================================================================
-// Media Handlers
+// Post Handlers
 // =============================================================================
 
-use axum::extract::Multipart;
-use rustpress_api::services::media_service::{
-    validate_upload, MediaListParams, MediaService, UpdateMediaRequest as MediaUpdateRequest,
+use crate::services::content_path;
+use rustpress_api::services::post_service::{
+    CalendarParams, CreatePostRequest, PostListParams, PostService, ReschedulePostRequest,
+    UpdatePostRequest,
 };
-use std::io::Write;
 
-/// Media list query parameters
+/// Post list query parameters
 #[derive(Debug, serde::Deserialize)]
-struct MediaListQuery {
+struct PostListQuery {
     page: Option<u32>,
     per_page: Option<u32>,
-    media_type: Option<String>,
-    mime_type: Option<String>,
-    uploader_id: Option<Uuid>,
+    status: Option<String>,
+    author_id: Option<Uuid>,
     search: Option<String>,
     sort_by: Option<String>,
     sort_order: Option<String>,
+    filter: Option<String>,
 }
 
-async fn list_media_handler(
-    Query(query): Query<MediaListQuery>,
+async fn list_posts_handler(
+    Query(query): Query<PostListQuery>,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let service = MediaService::new(state.db().inner().clone());
+    let service = PostService::new(state.db().inner().clone());
 
-    let params = MediaListParams {
+    let params = PostListParams {
         page: query.page,
         per_page: query.per_page,
-        media_type: query.media_type,
-        mime_type: query.mime_type,
-        uploader_id: query.uploader_id,
+        status: query.status,
+        author_id: query.author_id,
         search: query.search,
         sort_by: query.sort_by,
         sort_order: query.sort_order,
+        filter: query.filter,
     };
 
-    let result = service.list_media(params).await?;
+    let result = service.list_posts(params).await?;
     Ok(json(result))
 }
 
-async fn upload_media_handler(
+async fn create_post_handler(
     user: AuthUser,
     State(state): State<AppState>,
-    mut multipart: Multipart,
+    Json(mut payload): Json<CreatePostRequest>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let service = MediaService::new(state.db().inner().clone());
-
-    // Process multipart form data
-    let mut file_data: Option<Vec<u8>> = None;
-    let mut original_filename = String::new();
-    let mut content_type = String::from("application/octet-stream");
-    let mut alt_text: Option<String> = None;
-    let mut title: Option<String> = None;
-    let mut description: Option<String> = None;
-
-    while let Some(field) = multipart.next_field().await.map_err(|e| {
-        rustpress_core::error::Error::validation(format!("Failed to read multipart: {}", e))
-    })? {
-        let name = field.name().unwrap_or("").to_string();
-
-        match name.as_str() {
-            "file" => {
-                original_filename = field.file_name().unwrap_or("upload").to_string();
-                if let Some(ct) = field.content_type() {
-                    content_type = ct.to_string();
-                }
-                file_data = Some(
-                    field
-                        .bytes()
-                        .await
-                        .map_err(|e| {
-                            rustpress_core::error::Error::validation(format!(
-                                "Failed to read file: {}",
-                                e
-                            ))
-                        })?
-                        .to_vec(),
-                );
-            }
-            "alt_text" => {
-                alt_text = field.text().await.ok();
-            }
-            "title" => {
-                title = field.text().await.ok();
-            }
-            "description" => {
-                description = field.text().await.ok();
-            }
-            _ => {}
-        }
-    }
-
-    let data =
-        file_data.ok_or_else(|| rustpress_core::error::Error::validation("No file uploaded"))?;
-    let file_size = data.len() as u64;
-
-    // Validate the upload
-    validate_upload(&original_filename, file_size)?;
-
-    // Generate unique filename
-    let ext = original_filename.rsplit('.').next().unwrap_or("bin");
-    let unique_filename = format!(
-        "{}_{}.{}",
-        Uuid::new_v4(),
-        chrono::Utc::now().timestamp(),
-        ext
-    );
-
-    // Storage path (relative)
-    let now = chrono::Utc::now();
-    let storage_path = format!(
-        "{}/{}/{}/{}",
-        now.format("%Y"),
-        now.format("%m"),
-        now.format("%d"),
-        unique_filename
-    );
-
-    // Create directory and save file
-    let uploads_dir = std::path::Path::new("uploads");
-    let full_path = uploads_dir.join(&storage_path);
-
-    if let Some(parent) = full_path.parent() {
-        std::fs::create_dir_all(parent).map_err(|e| {
-            rustpress_core::error::Error::internal(format!("Failed to create directory: {}", e))
-        })?;
-    }
-
-    let mut file = std::fs::File::create(&full_path).map_err(|e| {
-        rustpress_core::error::Error::internal(format!("Failed to create file: {}", e))
-    })?;
-
-    file.write_all(&data).map_err(|e| {
-        rustpress_core::error::Error::internal(format!("Failed to write file: {}", e))
-    })?;
-
-    // Create database record
-    let metadata = rustpress_api::services::media_service::UploadMediaMetadata {
-        alt_text,
-        title,
-        description,
-    };
-
-    let media = service
-        .upload_media(
-            user.id,
-            unique_filename,
-            original_filename,
-            content_type,
-            file_size as i64,
-            storage_path,
-            Some(metadata),
-            None, // dimensions - would need image processing
-            None, // duration - would need audio/video processing
-        )
-        .await?;
-
-    Ok(created(media))
+    check_section_access(&state, &user, None, payload.category_ids.as_deref()).await?;
+    let service = PostService::new(state.db().inner().clone());
+    let sanitized = sanitize_content(&state, &user, &mut payload.content).await;
+    let post = service.create_post(payload, user.id).await?;
+    update_content_redirects(&state, "post", None, &post.slug, &post.status).await;
+    record_sanitization(&state, &user, post.id, sanitized).await;
+    record_media_usage(
+        &state,
+        "post",
+        post.id,
+        post.content.as_deref(),
+        post.featured_image_id,
+    )
+    .await;
+    state.page_cache.purge_post(post.id, Vec::new()).await;
+    Ok(created(post))
 }
 
-async fn get_media_handler(
+async fn get_post_handler(
     PathId(id): PathId,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let service = MediaService::new(state.db().inner().clone());
+    let service = PostService::new(state.db().inner().clone());
 
-    match service.get_media(id).await? {
-        Some(media) => Ok(json(media)),
-        None => Err(rustpress_core::error::Error::not_found("Media", id.to_string()).into()),
+    match service.get_post(id).await? {
+        Some(post) => {
+            let version = post.version;
+            Ok(versioned(post, version))
+        }
+        None => Err(rustpress_core::error::Error::not_found("Post", id.to_string()).into()),
     }
 }
 
-async fn update_media_handler(
-    _user: AuthUser,
+async fn update_post_handler(
+    user: AuthUser,
     PathId(id): PathId,
     State(state): State<AppState>,
-    Json(payload): Json<MediaUpdateRequest>,
+    IfMatch(if_match): IfMatch,
+    Json(mut payload): Json<UpdatePostRequest>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let service = MediaService::new(state.db().inner().clone());
-    let media = service.update_media(id, payload).await?;
-    Ok(json(media))
+    check_section_access(&state, &user, Some(id), payload.category_ids.as_deref()).await?;
+    let service = PostService::new(state.db().inner().clone());
+    payload.version = if_match.or(payload.version);
+    let sanitized = sanitize_content(&state, &user, &mut payload.content).await;
+    let before = state.page_cache.post_keys(id).await;
+    let permalink = current_permalink(&state, id).await?;
+    let post = service.update_post(id, payload).await?;
+    update_content_redirects(&state, "post", permalink, &post.slug, &post.status).await;
+    record_sanitization(&state, &user, id, sanitized).await;
+    record_media_usage(
+        &state,
+        "post",
+        id,
+        post.content.as_deref(),
+        post.featured_image_id,
+    )
+    .await;
+    state.page_cache.purge_post(id, before).await;
+    let version = post.version;
+    Ok(versioned(post, version))
 }
 
-async fn delete_media_handler(
-    _user: AuthUser,
+async fn delete_post_handler(
+    user: AuthUser,
     PathId(id): PathId,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let service = MediaService::new(state.db().inner().clone());
-    service.delete_media(id).await?;
+    check_section_access(&state, &user, Some(id), None).await?;
+    let service = PostService::new(state.db().inner().clone());
+    let before = state.page_cache.post_keys(id).await;
+    service.delete_post(id).await?;
+    clear_media_usage(&state, "post", id).await;
+    state.page_cache.purge_post(id, before).await;
     Ok(no_content())
 }
 
-/// List media folders
-async fn list_media_folders_handler(
+async fn publish_post_handler(
+    user: AuthUser,
+    PathId(id): PathId,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
-
-    let folders: Vec<(Uuid, String, Option<Uuid>)> =
-        sqlx::query_as("SELECT id, name, parent_id FROM media_folders ORDER BY name")
-            .fetch_all(pool)
-            .await
-            .unwrap_or_default();
+    let owners = check_section_access(&state, &user, Some(id), None).await?;
+    let service = PostService::new(state.db().inner().clone());
+    let post = service.publish_post(id).await?;
+    update_content_redirects(&state, "post", None, &post.slug, &post.status).await;
+    state.page_cache.purge_post(id, Vec::new()).await;
+    state
+        .publish(
+            user_event(
+                Some(&user),
+                "post.published",
+                serde_json::json!({
+                    "id": post.id,
+                    "title": post.title,
+                    "slug": post.slug,
+                    "author_id": post.author_id,
+                    "published_at": post.published_at,
+                }),
+            )
+            .with_aggregate(post.id, "post"),
+        )
+        .await;
 
-    let folder_list: Vec<serde_json::Value> = folders
-        .iter()
-        .map(|(id, name, parent_id)| {
+    // Let the teams owning the post's section know
+    if !owners.is_empty() {
+        let notified = publish_group_notification(
+            &state,
+            &user,
+            &owners,
             serde_json::json!({
-                "id": id,
-                "name": name,
-                "parent_id": parent_id
-            })
-        })
-        .collect();
-
-    Ok(json(serde_json::json!({ "folders": folder_list })))
-}
-
-/// Create media folder request
-#[derive(Debug, Deserialize)]
-struct CreateMediaFolderRequest {
-    name: String,
-    parent_id: Option<Uuid>,
-}
-
-/// Create media folder
-async fn create_media_folder_handler(
-    _user: AuthUser,
-    State(state): State<AppState>,
-    Json(payload): Json<CreateMediaFolderRequest>,
-) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
-    let id = Uuid::now_v7();
-    let now = chrono::Utc::now();
-
-    sqlx::query(
-        "INSERT INTO media_folders (id, name, parent_id, created_at, updated_at) VALUES ($1, $2, $3, $4, $4)"
-    )
-    .bind(id)
-    .bind(&payload.name)
-    .bind(&payload.parent_id)
-    .bind(now)
-    .execute(pool)
-    .await
-    .map_err(|e| rustpress_core::error::Error::database_with_source("Failed to create folder", e))?;
-
-    Ok(created(
-        serde_json::json!({ "id": id, "name": payload.name }),
-    ))
-}
-
-// =============================================================================
-// Comment Handlers
-// =============================================================================
-
-use rustpress_api::services::comment_service::{
-    BatchModerateRequest, CommentService, CreateCommentRequest as CommentCreateRequest,
-    UpdateCommentRequest as CommentUpdateRequest,
-};
-use rustpress_database::repository::comments::CommentStatus;
-
-/// Comment list query parameters
-#[derive(Debug, serde::Deserialize)]
-struct CommentListQuery {
-    page: Option<u32>,
-    per_page: Option<u32>,
-    post_id: Option<Uuid>,
-    status: Option<String>,
-    search: Option<String>,
+                "group_ids": owners,
+                "title": "Post published",
+                "message": post.title,
+                "post_id": post.id,
+                "sender_id": user.id,
+            }),
+        )
+        .await;
+        if let Err(e) = notified {
+            tracing::warn!(post_id = %post.id, "Failed to notify section owners: {}", e);
+        }
+    }
+    Ok(json(post))
 }
 
-async fn list_comments_handler(
+async fn unpublish_post_handler(
     user: AuthUser,
-    Query(query): Query<CommentListQuery>,
+    PathId(id): PathId,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let service = CommentService::new(state.db().inner().clone());
-
-    let status = query.status.and_then(|s| s.parse::<CommentStatus>().ok());
-
-    let result = service
-        .list_comments(
-            query.page.unwrap_or(1).into(),
-            query.per_page.unwrap_or(20).into(),
-            query.post_id,
-            status,
-            query.search,
-        )
-        .await?;
-
-    Ok(json(result))
+    check_section_access(&state, &user, Some(id), None).await?;
+    let service = PostService::new(state.db().inner().clone());
+    let post = service.unpublish_post(id).await?;
+    state.page_cache.purge_post(id, Vec::new()).await;
+    Ok(json(post))
 }
 
-async fn create_comment_handler(
-    user: Option<AuthUser>,
+/// Posts by date for the editorial calendar
+async fn post_calendar_handler(
+    user: AuthUser,
     State(state): State<AppState>,
-    ConnectInfo(addr): ConnectInfo<std::net::SocketAddr>,
-    headers: axum::http::HeaderMap,
-    Json(payload): Json<CommentCreateRequest>,
+    Query(params): Query<CalendarParams>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let service = CommentService::new(state.db().inner().clone());
-
-    let user_id = user.map(|u| u.id);
-    let ip = Some(addr.ip().to_string());
-    let user_agent = headers
-        .get(axum::http::header::USER_AGENT)
-        .and_then(|v| v.to_str().ok())
-        .map(String::from);
-
-    let comment = service
-        .submit_comment(payload, user_id, ip, user_agent)
-        .await?;
-
-    Ok(created(comment))
+    require_permission(&user, &state, "posts", "edit").await?;
+    let service = PostService::new(state.db().inner().clone());
+    Ok(json(service.calendar(params).await?))
 }
 
-async fn get_comment_handler(
+/// Move a post to another day on the editorial calendar. Moving scheduled
+/// and published posts needs `posts:publish`.
+async fn reschedule_post_handler(
+    user: AuthUser,
     PathId(id): PathId,
     State(state): State<AppState>,
+    IfMatch(if_match): IfMatch,
+    Json(mut payload): Json<ReschedulePostRequest>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let service = CommentService::new(state.db().inner().clone());
+    check_section_access(&state, &user, Some(id), None).await?;
+    let (slug, status) = current_permalink(&state, id)
+        .await?
+        .ok_or_else(|| rustpress_core::error::Error::not_found("Post", id.to_string()))?;
+    let action = match status.as_str() {
+        "scheduled" | "published" | "private" => "publish",
+        _ => "edit",
+    };
+    require_permission(&user, &state, "posts", action).await?;
 
-    match service.get_comment(id).await? {
-        Some(comment) => Ok(json(comment)),
-        None => Err(rustpress_core::error::Error::not_found("Comment", id.to_string()).into()),
+    let service = PostService::new(state.db().inner().clone());
+    payload.version = if_match.or(payload.version);
+    let before = state.page_cache.post_keys(id).await;
+    let moved = service.reschedule_post(id, payload).await?;
+    if moved.post.status == "published" {
+        state.page_cache.purge_post(id, before).await;
     }
+    state
+        .publish(
+            user_event(
+                Some(&user),
+                "post.rescheduled",
+                serde_json::json!({
+                    "id": id,
+                    "title": moved.post.title,
+                    "slug": slug,
+                    "status": moved.post.status,
+                    "previous_at": moved.previous_at,
+                    "scheduled_at": moved.post.scheduled_at,
+                    "published_at": moved.post.published_at,
+                }),
+            )
+            .with_aggregate(id, "post"),
+        )
+        .await;
+    let version = moved.post.version;
+    Ok(versioned(moved, version))
 }
 
-async fn update_comment_handler(
-    user: AuthUser,
-    PathId(id): PathId,
-    State(state): State<AppState>,
-    Json(payload): Json<CommentUpdateRequest>,
-) -> HttpResult<impl axum::response::IntoResponse> {
-    let service = CommentService::new(state.db().inner().clone());
-    let comment = service.update_comment(id, payload).await?;
-    Ok(json(comment))
+/// Slug and status of a post or page, before an edit changes them
+async fn current_permalink(state: &AppState, id: Uuid) -> HttpResult<Option<(String, String)>> {
+    sqlx::query_as(&format!(
+        "SELECT slug, status FROM posts WHERE id = $1 AND deleted_at IS NULL AND {}",
+        site_filter("site_id")
+    ))
+    .bind(id)
+    .fetch_optional(state.db().inner())
+    .await
+    .map_err(|e| {
+        rustpress_core::error::Error::database_with_source("Failed to load post", e).into()
+    })
 }
 
-async fn delete_comment_handler(
-    user: AuthUser,
-    PathId(id): PathId,
-    State(state): State<AppState>,
-) -> HttpResult<impl axum::response::IntoResponse> {
-    let service = CommentService::new(state.db().inner().clone());
-    service.delete_comment(id).await?;
-    Ok(no_content())
+/// Keep redirects in step with where published content lives: a renamed
+/// post or page gets a redirect from its old path, and a redirect from the
+/// path it's now published at is dropped
+async fn update_content_redirects(
+    state: &AppState,
+    post_type: &str,
+    before: Option<(String, String)>,
+    slug: &str,
+    status: &str,
+) {
+    let path = content_path(post_type, slug);
+    let result = match before {
+        Some((old_slug, old_status)) if old_status == "published" && old_slug != slug => {
+            state
+                .redirects
+                .record_move(&content_path(post_type, &old_slug), &path)
+                .await
+        }
+        _ if status == "published" => state.redirects.release(&path).await,
+        _ => Ok(()),
+    };
+    if let Err(e) = result {
+        tracing::warn!(path, "Failed to update redirects: {}", e);
+    }
 }
 
-async fn approve_comment_handler(
-    user: AuthUser,
-    PathId(id): PathId,
-    State(state): State<AppState>,
-) -> HttpResult<impl axum::response::IntoResponse> {
-    let service = CommentService::new(state.db().inner().clone());
-    let comment = service.approve_comment(id, user.id).await?;
-    Ok(json(comment))
+/// Refuse changes to a post in, or being filed in, a section owned by groups
+/// the user doesn't belong to. Returns the groups owning the post's current
+/// section, if any.
+pub(crate) async fn check_section_access(
+    state: &AppState,
+    user: &AuthUser,
+    post_id: Option<Uuid>,
+    category_ids: Option<&[Uuid]>,
+) -> HttpResult<Vec<Uuid>> {
+    let owners = match post_id {
+        Some(id) => state.groups.post_owners(id).await?,
+        None => Vec::new(),
+    };
+    if user.is_admin() {
+        return Ok(owners);
+    }
+    if !user.groups.may_change_section(&owners) {
+        return Err(HttpError::forbidden(
+            "This post belongs to a section owned by a team you are not in",
+        ));
+    }
+    if let Some(category_ids) = category_ids {
+        let target = state.groups.section_owners(category_ids).await?;
+        if !user.groups.may_change_section(&target) {
+            return Err(HttpError::forbidden(
+                "Posts can only be filed in a team's section by its members",
+            ));
+        }
+    }
+    Ok(owners)
+}
+
+/// Sanitize submitted post or page content with the author's role policy
+async fn sanitize_content(
+    state: &AppState,
+    user: &AuthUser,
+    content: &mut Option<String>,
+) -> Option<SanitizedContent> {
+    let html = content.as_deref()?;
+    let sanitized = state
+        .sanitization
+        .sanitize_for_roles(&user.roles, html)
+        .await;
+    *content = Some(sanitized.html.clone());
+    Some(sanitized)
 }
 
-async fn mark_spam_handler(
-    user: AuthUser,
-    PathId(id): PathId,
-    State(state): State<AppState>,
-) -> HttpResult<impl axum::response::IntoResponse> {
-    let service = CommentService::new(state.db().inner().clone());
-    let comment = service.mark_as_spam(id, user.id).await?;
-    Ok(json(comment))
+/// Record which media a saved post or page uses
+async fn record_media_usage(
+    state: &AppState,
+    entity_type: &str,
+    id: Uuid,
+    content: Option<&str>,
+    featured_image_id: Option<Uuid>,
+) {
+    let service = MediaService::new(state.db().inner().clone());
+    let content = content.unwrap_or_default();
+    if let Err(e) = service
+        .sync_usage(entity_type, id, content, featured_image_id)
+        .await
+    {
+        tracing::warn!(%id, "Failed to record media usage: {}", e);
+    }
 }
 
-async fn trash_comment_handler(
-    user: AuthUser,
-    PathId(id): PathId,
-    State(state): State<AppState>,
-) -> HttpResult<impl axum::response::IntoResponse> {
-    let service = CommentService::new(state.db().inner().clone());
-    let comment = service.trash_comment(id, user.id).await?;
-    Ok(json(comment))
+/// Forget the media a deleted post or page used
+async fn clear_media_usage(state: &AppState, entity_type: &str, id: Uuid) {
+    let service = MediaService::new(state.db().inner().clone());
+    if let Err(e) = service.clear_usage(entity_type, id).await {
+        tracing::warn!(%id, "Failed to clear media usage: {}", e);
+    }
 }
 
-async fn batch_moderate_comments_handler(
-    user: AuthUser,
-    State(state): State<AppState>,
-    Json(payload): Json<BatchModerateRequest>,
-) -> HttpResult<impl axum::response::IntoResponse> {
-    let service = CommentService::new(state.db().inner().clone());
-    let updated = service.batch_moderate(payload, user.id).await?;
-    Ok(json(serde_json::json!({ "updated": updated })))
+/// Keep the sanitization report of a saved post or page
+async fn record_sanitization(
+    state: &AppState,
+    user: &AuthUser,
+    post_id: Uuid,
+    sanitized: Option<SanitizedContent>,
+) {
+    if let Some(sanitized) = sanitized {
+        state
+            .sanitization
+            .record_quietly(
+                ContentSource::Post,
+                Some(post_id),
+                Some(user.id),
+                &sanitized,
+            )
+            .await;
+    }
 }
 
-async fn toggle_like_handler(
-    user: AuthUser,
-    PathId(id): PathId,
-    State(state): State<AppState>,
-) -> HttpResult<impl axum::response::IntoResponse> {
-    let service = CommentService::new(state.db().inner().clone());
-    let liked = service.toggle_like(id, user.id).await?;
-    Ok(json(serde_json::json!({ "liked": liked })))
+/// Bulk delete posts request
+#[derive(Debug, serde::Deserialize)]
+struct BulkDeletePostsRequest {
+    ids: Vec<Uuid>,
 }
 
-async fn comment_counts_handler(
+/// Bulk delete posts response
+#[derive(Debug, serde::Serialize)]
+struct BulkDeletePostsResponse {
+    deleted: usize,
+}
+
+async fn bulk_delete_posts_handler(
     user: AuthUser,
     State(state): State<AppState>,
+    Json(payload): Json<BulkDeletePostsRequest>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let service = CommentService::new(state.db().inner().clone());
-    let counts = service.get_counts().await?;
-    Ok(json(counts))
-}
+    let service = PostService::new(state.db().inner().clone());
 
-// =============================================================================
-// Settings Handlers
-// =============================================================================
+    let mut deleted_count = 0;
+    for id in payload.ids {
+        let before = state.page_cache.post_keys(id).await;
+        if service.delete_post(id).await? {
+            deleted_count += 1;
+            state.page_cache.purge_post(id, before).await;
+        }
+    }
 
-use rustpress_api::services::settings_service::{
-    BatchUpdateRequest, SettingUpdate, SettingsService,
-};
+    Ok(json(BulkDeletePostsResponse {
+        deleted: deleted_count,
+    }))
+}
 
-/// List all settings grouped
-async fn list_settings_handler(
+async fn duplicate_post_handler(
     user: AuthUser,
+    PathId(id): PathId,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let service = SettingsService::new(state.db().inner().clone());
-    let settings = service.get_all_grouped().await?;
-    Ok(json(settings))
-}
+    let service = PostService::new(state.db().inner().clone());
 
-/// Get a single setting by key
-async fn get_setting_handler(
-    user: AuthUser,
-    axum::extract::Path(key): axum::extract::Path<String>,
-    State(state): State<AppState>,
-) -> HttpResult<impl axum::response::IntoResponse> {
-    let service = SettingsService::new(state.db().inner().clone());
-    match service.get(&key).await? {
-        Some(setting) => Ok(json(setting)),
-        None => Err(crate::error::HttpError::not_found("Setting not found")),
-    }
-}
-
-/// Update a single setting
-async fn update_setting_handler(
-    user: AuthUser,
-    axum::extract::Path(key): axum::extract::Path<String>,
-    State(state): State<AppState>,
-    Json(payload): Json<serde_json::Value>,
-) -> HttpResult<impl axum::response::IntoResponse> {
-    let service = SettingsService::new(state.db().inner().clone());
+    // Get the original post
+    let original =
+        service
+            .get_post(id)
+            .await?
+            .ok_or_else(|| rustpress_core::error::Error::NotFound {
+                entity_type: "post".to_string(),
+                id: id.to_string(),
+            })?;
 
-    // Extract value from payload (support both { "value": x } and direct value)
-    let value = if let Some(v) = payload.get("value") {
-        v.clone()
-    } else {
-        payload
+    // Create a duplicate with modified title
+    let duplicate_request = CreatePostRequest {
+        title: format!("{} (Copy)", original.title),
+        slug: None, // Let the service generate a new slug
+        content: original.content,
+        content_format: original.content_format,
+        excerpt: original.excerpt,
+        status: Some("draft".to_string()), // Always create as draft
+        visibility: original.visibility,
+        password: None,
+        featured_image_id: original.featured_image_id,
+        comment_status: original.comment_status,
+        ping_status: original.ping_status,
+        published_at: None,
+        category_ids: if original.categories.is_empty() {
+            None
+        } else {
+            Some(original.categories.iter().map(|c| c.id).collect())
+        },
+        tag_ids: if original.tags.is_empty() {
+            None
+        } else {
+            Some(original.tags.iter().map(|t| t.id).collect())
+        },
+        scheduled_at: None,
+        expires_at: None,
+        embargo_starts_at: None,
+        embargo_ends_at: None,
+        timezone: original.timezone,
     };
 
-    let updated = service.update(&key, value).await?;
-    Ok(json(updated))
+    let new_post = service.create_post(duplicate_request, user.id).await?;
+    Ok(created(new_post))
 }
 
-/// Batch update multiple settings
-async fn batch_update_settings_handler(
-    user: AuthUser,
-    State(state): State<AppState>,
-    Json(payload): Json<BatchUpdateRequest>,
-) -> HttpResult<impl axum::response::IntoResponse> {
-    let service = SettingsService::new(state.db().inner().clone());
-    let updated = service.batch_update(payload.settings).await?;
-    Ok(json(serde_json::json!({
-        "updated": updated.len(),
-        "settings": updated
-    })))
-}
+// =============================================================================
+// Page Handlers
+// =============================================================================
 
-/// Get settings by group
-async fn get_settings_group_handler(
-    user: AuthUser,
-    axum::extract::Path(group): axum::extract::Path<String>,
-    State(state): State<AppState>,
-) -> HttpResult<impl axum::response::IntoResponse> {
-    let service = SettingsService::new(state.db().inner().clone());
-    let settings = service.get_by_group(&group).await?;
-    Ok(json(settings))
+use rustpress_api::services::page_service::{
+    CreatePageRequest, PageListParams, PageService, UpdatePageRequest,
+};
+
+/// Page list query parameters
+#[derive(Debug, serde::Deserialize)]
+struct PageListQuery {
+    page: Option<u32>,
+    per_page: Option<u32>,
+    status: Option<String>,
+    parent_id: Option<Uuid>,
+    search: Option<String>,
+    sort_by: Option<String>,
+    sort_order: Option<String>,
+    hierarchical: Option<bool>,
 }
 
-/// Get general settings
-async fn get_general_settings_handler(
-    user: AuthUser,
+async fn list_pages_handler(
+    Query(query): Query<PageListQuery>,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let service = SettingsService::new(state.db().inner().clone());
-    let settings = service.get_by_group("general").await?;
-    Ok(json(settings))
+    let service = PageService::new(state.db().inner().clone());
+
+    let params = PageListParams {
+        page: query.page,
+        per_page: query.per_page,
+        status: query.status,
+        parent_id: query.parent_id,
+        search: query.search,
+        sort_by: query.sort_by,
+        sort_order: query.sort_order,
+        hierarchical: query.hierarchical,
+    };
+
+    let result = service.list_pages(params).await?;
+    Ok(json(result))
 }
 
-/// Get reading settings
-async fn get_reading_settings_handler(
+async fn create_page_handler(
     user: AuthUser,
     State(state): State<AppState>,
+    Json(mut payload): Json<CreatePageRequest>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let service = SettingsService::new(state.db().inner().clone());
-    let settings = service.get_by_group("reading").await?;
-    Ok(json(settings))
+    let service = PageService::new(state.db().inner().clone());
+    let sanitized = sanitize_content(&state, &user, &mut payload.content).await;
+    let page = service.create_page(payload, user.id).await?;
+    update_content_redirects(&state, "page", None, &page.slug, &page.status).await;
+    record_sanitization(&state, &user, page.id, sanitized).await;
+    record_media_usage(
+        &state,
+        "page",
+        page.id,
+        page.content.as_deref(),
+        page.featured_image_id,
+    )
+    .await;
+    state.page_cache.purge_post(page.id, Vec::new()).await;
+    Ok(created(page))
 }
 
-/// Get writing settings
-async fn get_writing_settings_handler(
-    user: AuthUser,
+async fn get_page_handler(
+    PathId(id): PathId,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let service = SettingsService::new(state.db().inner().clone());
-    let settings = service.get_by_group("writing").await?;
-    Ok(json(settings))
+    let service = PageService::new(state.db().inner().clone());
+
+    match service.get_page(id).await? {
+        Some(page) => Ok(json(page)),
+        None => Err(rustpress_core::error::Error::not_found("Page", id.to_string()).into()),
+    }
 }
 
-/// Get discussion settings
-async fn get_discussion_settings_handler(
+async fn update_page_handler(
     user: AuthUser,
+    PathId(id): PathId,
     State(state): State<AppState>,
+    Json(mut payload): Json<UpdatePageRequest>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let service = SettingsService::new(state.db().inner().clone());
-    let settings = service.get_by_group("discussion").await?;
-    Ok(json(settings))
+    let service = PageService::new(state.db().inner().clone());
+    let sanitized = sanitize_content(&state, &user, &mut payload.content).await;
+    let before = state.page_cache.post_keys(id).await;
+    let permalink = current_permalink(&state, id).await?;
+    let page = service.update_page(id, payload).await?;
+    update_content_redirects(&state, "page", permalink, &page.slug, &page.status).await;
+    record_sanitization(&state, &user, id, sanitized).await;
+    record_media_usage(
+        &state,
+        "page",
+        id,
+        page.content.as_deref(),
+        page.featured_image_id,
+    )
+    .await;
+    state.page_cache.purge_post(id, before).await;
+    Ok(json(page))
 }
 
-/// Get permalinks settings
-async fn get_permalinks_settings_handler(
+async fn delete_page_handler(
     user: AuthUser,
+    PathId(id): PathId,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let service = SettingsService::new(state.db().inner().clone());
-    let settings = service.get_by_group("permalinks").await?;
-    Ok(json(settings))
+    let service = PageService::new(state.db().inner().clone());
+    let before = state.page_cache.post_keys(id).await;
+    service.delete_page(id).await?;
+    clear_media_usage(&state, "page", id).await;
+    state.page_cache.purge_post(id, before).await;
+    Ok(no_content())
 }
 
 // =============================================================================
-// Storage Configuration Handlers
+// Media Handlers
 // =============================================================================
 
-use rustpress_api::services::storage_service::{
-    MigrationRequest, ProviderConfig, StorageCategory, StorageConfigRequest, StorageProvider,
-    StorageService as StorageConfigService,
+use axum::extract::Multipart;
+use rustpress_api::handlers::media::{
+    BulkDeleteRequest, BulkMoveRequest, BulkTagRequest, CreateFolderRequest, UpdateFolderRequest,
+};
+use rustpress_api::services::media_service::{
+    validate_upload, MediaListParams, MediaService, UpdateMediaRequest as MediaUpdateRequest,
 };
+use std::io::Write;
 
-/// List all storage configurations
-async fn list_storage_configurations_handler(
-    user: AuthUser,
-    State(state): State<AppState>,
-) -> HttpResult<impl axum::response::IntoResponse> {
-    let service = StorageConfigService::new(state.db().inner().clone());
-    let configurations = service.get_all_configurations().await?;
-    Ok(json(
-        serde_json::json!({ "configurations": configurations }),
-    ))
+/// Media list query parameters
+#[derive(Debug, serde::Deserialize)]
+struct MediaListQuery {
+    page: Option<u32>,
+    per_page: Option<u32>,
+    media_type: Option<String>,
+    mime_type: Option<String>,
+    uploader_id: Option<Uuid>,
+    search: Option<String>,
+    sort_by: Option<String>,
+    sort_order: Option<String>,
+    filter: Option<String>,
+    folder_id: Option<Uuid>,
+    tag: Option<String>,
+    #[serde(default)]
+    missing_alt: bool,
+    #[serde(default)]
+    unused: bool,
 }
 
-/// Get storage configuration for a specific category
-async fn get_storage_configuration_handler(
-    user: AuthUser,
-    axum::extract::Path(category): axum::extract::Path<String>,
+async fn list_media_handler(
+    Query(query): Query<MediaListQuery>,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let category: StorageCategory = category.parse().map_err(|e| {
-        rustpress_core::error::Error::validation(format!("Invalid category: {}", category))
-    })?;
+    let service = MediaService::new(state.db().inner().clone());
 
-    let service = StorageConfigService::new(state.db().inner().clone());
-    let configuration = service.get_or_create_default(&category).await?;
-    Ok(json(configuration))
+    let params = MediaListParams {
+        page: query.page,
+        per_page: query.per_page,
+        media_type: query.media_type,
+        mime_type: query.mime_type,
+        uploader_id: query.uploader_id,
+        search: query.search,
+        sort_by: query.sort_by,
+        sort_order: query.sort_order,
+        filter: query.filter,
+        folder_id: query.folder_id,
+        tag: query.tag,
+        missing_alt: query.missing_alt,
+        unused: query.unused,
+    };
+
+    let result = service.list_media(params).await?;
+    Ok(json(result))
 }
 
-/// Update storage configuration for a category
-async fn update_storage_configuration_handler(
+async fn upload_media_handler(
     user: AuthUser,
-    axum::extract::Path(category): axum::extract::Path<String>,
     State(state): State<AppState>,
-    Json(request): Json<StorageConfigRequest>,
+    mut multipart: Multipart,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let category: StorageCategory = category.parse().map_err(|e| {
-        rustpress_core::error::Error::validation(format!("Invalid category: {}", category))
-    })?;
-
-    let service = StorageConfigService::new(state.db().inner().clone());
-    let configuration = service.update_configuration(&category, request).await?;
-    Ok(json(serde_json::json!({
-        "message": "Storage configuration updated",
-        "configuration": configuration
-    })))
-}
+    let service = MediaService::new(state.db().inner().clone());
 
-/// Test storage connection request
-#[derive(Debug, Deserialize)]
-struct TestConnectionRequest {
-    provider: StorageProvider,
-    config: ProviderConfig,
-}
+    // Process multipart form data
+    let mut file_data: Option<Vec<u8>> = None;
+    let mut original_filename = String::new();
+    let mut content_type = String::from("application/octet-stream");
+    let mut alt_text: Option<String> = None;
+    let mut title: Option<String> = None;
+    let mut description: Option<String> = None;
+    let mut folder_id: Option<Uuid> = None;
 
-/// Test storage connection
-async fn test_storage_connection_handler(
-    user: AuthUser,
-    State(state): State<AppState>,
-    Json(request): Json<TestConnectionRequest>,
-) -> HttpResult<impl axum::response::IntoResponse> {
-    let service = StorageConfigService::new(state.db().inner().clone());
-    let result = service
-        .test_connection(&request.provider, &request.config)
+    while let Some(field) = multipart.next_field().await.map_err(|e| {
+        rustpress_core::error::Error::validation(format!("Failed to read multipart: {}", e))
+    })? {
+        let name = field.name().unwrap_or("").to_string();
+
+        match name.as_str() {
+            "file" => {
+                original_filename = field.file_name().unwrap_or("upload").to_string();
+                if let Some(ct) = field.content_type() {
+                    content_type = ct.to_string();
+                }
+                file_data = Some(
+                    field
+                        .bytes()
+                        .await
+                        .map_err(|e| {
+                            rustpress_core::error::Error::validation(format!(
+                                "Failed to read file: {}",
+                                e
+                            ))
+                        })?
+                        .to_vec(),
+                );
+            }
+            "alt_text" => {
+                alt_text = field.text().await.ok();
+            }
+            "title" => {
+                title = field.text().await.ok();
+            }
+            "description" => {
+                description = field.text().await.ok();
+            }
+            "folder_id" => {
+                let value = field.text().await.unwrap_or_default();
+                if !value.is_empty() {
+                    folder_id = Some(Uuid::parse_str(&value).map_err(|_| {
+                        rustpress_core::error::Error::invalid_input(
+                            "folder_id",
+                            "Invalid folder ID",
+                        )
+                    })?);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let data =
+        file_data.ok_or_else(|| rustpress_core::error::Error::validation("No file uploaded"))?;
+    let file_size = data.len() as u64;
+
+    // Validate the upload
+    validate_upload(&original_filename, file_size)?;
+
+    // Generate unique filename
+    let ext = original_filename.rsplit('.').next().unwrap_or("bin");
+    let unique_filename = format!(
+        "{}_{}.{}",
+        Uuid::new_v4(),
+        chrono::Utc::now().timestamp(),
+        ext
+    );
+
+    // Storage path (relative)
+    let now = chrono::Utc::now();
+    let storage_path = format!(
+        "{}/{}/{}/{}",
+        now.format("%Y"),
+        now.format("%m"),
+        now.format("%d"),
+        unique_filename
+    );
+
+    // Create directory and save file
+    let uploads_dir = std::path::Path::new("uploads");
+    let full_path = uploads_dir.join(&storage_path);
+
+    if let Some(parent) = full_path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| {
+            rustpress_core::error::Error::internal(format!("Failed to create directory: {}", e))
+        })?;
+    }
+
+    let mut file = std::fs::File::create(&full_path).map_err(|e| {
+        rustpress_core::error::Error::internal(format!("Failed to create file: {}", e))
+    })?;
+
+    file.write_all(&data).map_err(|e| {
+        rustpress_core::error::Error::internal(format!("Failed to write file: {}", e))
+    })?;
+
+    // Create database record
+    let metadata = rustpress_api::services::media_service::UploadMediaMetadata {
+        alt_text,
+        title,
+        description,
+        folder_id,
+    };
+
+    let media = service
+        .upload_media(
+            user.id,
+            unique_filename,
+            original_filename,
+            content_type,
+            file_size as i64,
+            storage_path,
+            Some(metadata),
+            None, // dimensions - would need image processing
+            None, // duration - would need audio/video processing
+        )
         .await?;
-    Ok(json(result))
-}
 
-/// Start a migration job
-async fn start_migration_handler(
-    user: AuthUser,
-    State(state): State<AppState>,
-    Json(request): Json<MigrationRequest>,
-) -> HttpResult<impl axum::response::IntoResponse> {
-    let service = StorageConfigService::new(state.db().inner().clone());
-    let status = service.start_migration(request).await?;
-    Ok(created(status))
+    Ok(created(media))
 }
 
-/// Get migration status
-async fn get_migration_status_handler(
-    user: AuthUser,
+async fn get_media_handler(
     PathId(id): PathId,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let service = StorageConfigService::new(state.db().inner().clone());
-    let status = service.get_migration_status(id).await?;
+    let service = MediaService::new(state.db().inner().clone());
 
-    match status {
-        Some(s) => Ok(json(s)),
-        None => Err(HttpError::not_found("Migration not found")),
+    match service.get_media(id).await? {
+        Some(media) => {
+            let version = media.version;
+            Ok(versioned(media, version))
+        }
+        None => Err(rustpress_core::error::Error::not_found("Media", id.to_string()).into()),
     }
 }
 
-/// Cancel a running migration
-async fn cancel_migration_handler(
-    user: AuthUser,
+async fn update_media_handler(
+    _user: AuthUser,
     PathId(id): PathId,
     State(state): State<AppState>,
+    IfMatch(if_match): IfMatch,
+    Json(mut payload): Json<MediaUpdateRequest>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let service = StorageConfigService::new(state.db().inner().clone());
-    let cancelled = service.cancel_migration(id).await?;
-
-    if cancelled {
-        Ok(json(
-            serde_json::json!({ "message": "Migration cancelled" }),
-        ))
-    } else {
-        Err(HttpError::bad_request(
-            "Migration not found or already completed",
-        ))
-    }
+    let service = MediaService::new(state.db().inner().clone());
+    payload.version = if_match.or(payload.version);
+    let media = service.update_media(id, payload).await?;
+    let version = media.version;
+    Ok(versioned(media, version))
 }
 
-// =============================================================================
-// Plugin Handlers (Placeholder implementations)
-// =============================================================================
-
-async fn list_plugins_handler(
-    user: AuthUser,
+async fn delete_media_handler(
+    _user: AuthUser,
+    PathId(id): PathId,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let plugins = state.plugins.read().await;
-    let plugin_list: Vec<serde_json::Value> = plugins
-        .list()
-        .iter()
-        .map(|info| {
-            serde_json::json!({
-                "id": info.id,
-                "name": info.name,
-                "version": info.version,
-                "description": info.description,
-                "author": info.author,
-                "active": true
-            })
-        })
-        .collect();
-    Ok(json(serde_json::json!({ "plugins": plugin_list })))
+    let service = MediaService::new(state.db().inner().clone());
+    service.delete_media(id).await?;
+    if let Err(e) = state.responsive_images.purge(id).await {
+        tracing::warn!(media_id = %id, "Failed to delete resized images: {}", e);
+    }
+    Ok(no_content())
 }
 
-async fn install_plugin_handler(
-    user: AuthUser,
+/// Folder tree with item counts and sizes
+async fn list_media_folders_handler(
     State(state): State<AppState>,
-    Json(payload): Json<serde_json::Value>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    Ok(created(serde_json::json!({ "message": "Install plugin" })))
+    let service = MediaService::new(state.db().inner().clone());
+    let folders = service.list_folders().await?;
+    Ok(json(serde_json::json!({ "folders": folders })))
 }
 
-async fn get_plugin_handler(
+async fn create_media_folder_handler(
     user: AuthUser,
-    PathId(id): PathId,
     State(state): State<AppState>,
+    Json(payload): Json<CreateFolderRequest>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let plugins = state.plugins.read().await;
-    if let Some(info) = plugins.list().iter().find(|p| p.id == id.to_string()) {
-        Ok(json(serde_json::json!({
-            "id": info.id,
-            "name": info.name,
-            "version": info.version,
-            "description": info.description,
-            "author": info.author,
-            "active": true
-        })))
-    } else {
-        Err(crate::error::HttpError::not_found(format!(
-            "Plugin '{}' not found",
-            id
-        )))
+    let name = payload.name.trim();
+    if name.is_empty() {
+        return Err(
+            rustpress_core::error::Error::invalid_input("name", "Folder name is required").into(),
+        );
     }
+
+    let service = MediaService::new(state.db().inner().clone());
+    let folder = service
+        .create_folder(
+            user.id,
+            name.to_string(),
+            payload.description,
+            payload.parent_id,
+            payload.color,
+            payload.icon,
+        )
+        .await?;
+    Ok(created(folder))
 }
 
-async fn uninstall_plugin_handler(
-    user: AuthUser,
+async fn get_media_folder_handler(
     PathId(id): PathId,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    Ok(no_content())
+    let service = MediaService::new(state.db().inner().clone());
+    match service.get_folder(id).await? {
+        Some(folder) => Ok(json(folder)),
+        None => Err(rustpress_core::error::Error::not_found("Folder", id.to_string()).into()),
+    }
 }
 
-async fn activate_plugin_handler(
-    user: AuthUser,
+async fn update_media_folder_handler(
+    _user: AuthUser,
     PathId(id): PathId,
     State(state): State<AppState>,
+    Json(payload): Json<UpdateFolderRequest>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    Ok(json(serde_json::json!({ "id": id, "active": true })))
+    let service = MediaService::new(state.db().inner().clone());
+    let folder = service
+        .update_folder(
+            id,
+            payload.name,
+            payload.description,
+            payload.parent_id.map(Some),
+            payload.color,
+            payload.icon,
+            payload.sort_order,
+        )
+        .await?;
+    Ok(json(folder))
 }
 
-async fn deactivate_plugin_handler(
-    user: AuthUser,
+/// Delete folder query parameters
+#[derive(Debug, Deserialize)]
+struct DeleteMediaFolderQuery {
+    /// Folder to move the media into; unfiled when absent
+    move_to: Option<Uuid>,
+}
+
+async fn delete_media_folder_handler(
+    _user: AuthUser,
     PathId(id): PathId,
+    Query(query): Query<DeleteMediaFolderQuery>,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    Ok(json(serde_json::json!({ "id": id, "active": false })))
+    let service = MediaService::new(state.db().inner().clone());
+    service.delete_folder(id, query.move_to).await?;
+    Ok(no_content())
 }
 
-// =============================================================================
-// Theme Handlers
-// =============================================================================
-
-/// List all installed themes
-async fn list_themes_handler(
+async fn bulk_move_media_handler(
+    _user: AuthUser,
     State(state): State<AppState>,
+    Json(payload): Json<BulkMoveRequest>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    // Get themes from database
-    let themes = state.theme_manager().list_themes().await?;
-    let active_theme = state.theme_manager().get_active_theme_id().await?;
-
-    Ok(json(serde_json::json!({
-        "themes": themes,
-        "total": themes.len(),
-        "active_theme": active_theme
-    })))
+    let service = MediaService::new(state.db().inner().clone());
+    let moved = service
+        .bulk_move(payload.media_ids, payload.folder_id)
+        .await?;
+    Ok(json(serde_json::json!({ "moved": moved })))
 }
 
-/// Scan themes directory and register new themes
-async fn scan_themes_handler(
-    user: AuthUser,
+async fn bulk_delete_media_handler(
+    _user: AuthUser,
     State(state): State<AppState>,
+    Json(payload): Json<BulkDeleteRequest>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    // Scan themes directory for new/updated themes
-    let scan_result = state.theme_manager().scan_themes().await?;
-
-    Ok(json(serde_json::json!({
-        "scanned": scan_result.scanned,
-        "registered": scan_result.registered,
-        "updated": scan_result.updated,
-        "errors": scan_result.errors
-    })))
+    let service = MediaService::new(state.db().inner().clone());
+    let result = service
+        .bulk_delete(payload.media_ids, payload.permanent.unwrap_or(false))
+        .await?;
+    Ok(json(result))
 }
 
-/// Get the currently active theme
-async fn get_active_theme_handler(
+async fn bulk_tag_media_handler(
+    _user: AuthUser,
     State(state): State<AppState>,
+    Json(payload): Json<BulkTagRequest>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let theme = state.theme_manager().get_active_theme().await?;
+    let service = MediaService::new(state.db().inner().clone());
+    let updated = service
+        .bulk_tag(payload.media_ids, payload.add_tags, payload.remove_tags)
+        .await?;
+    Ok(json(serde_json::json!({ "updated": updated })))
+}
 
-    match theme {
-        Some(t) => Ok(json(t)),
-        None => Err(rustpress_core::error::Error::not_found("Theme", "active").into()),
-    }
+/// Posts and pages that use a media item
+async fn media_usage_handler(
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let service = MediaService::new(state.db().inner().clone());
+    let usage = service.get_usage(id).await?;
+    Ok(json(serde_json::json!({ "media_id": id, "usage": usage })))
 }
 
-/// Get a specific theme by its ID
-async fn get_theme_handler(
-    axum::extract::Path(theme_id): axum::extract::Path<String>,
+/// Registered image sizes and the versions generated of a media item
+async fn media_sizes_handler(
+    PathId(id): PathId,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let theme = state.theme_manager().get_theme(&theme_id).await?;
+    let derivatives = state.responsive_images.derivatives(id).await?;
+    Ok(json(serde_json::json!({
+        "media_id": id,
+        "sizes": state.responsive_images.sizes(),
+        "derivatives": derivatives,
+    })))
+}
 
-    match theme {
-        Some(t) => Ok(json(t)),
-        None => Err(crate::error::HttpError::not_found(format!(
-            "Theme '{}' not found",
-            theme_id
-        ))),
-    }
+/// Delete the versions generated of a media item; they are made again
+/// when next requested
+async fn purge_media_sizes_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_permission(&user, &state, "media", "edit").await?;
+    let deleted = state.responsive_images.purge(id).await?;
+    Ok(json(serde_json::json!({ "deleted": deleted })))
 }
 
-/// Delete/uninstall a theme
-async fn delete_theme_handler(
+/// Get the render-time image loading and srcset settings
+async fn get_responsive_images_config_handler(
     user: AuthUser,
-    axum::extract::Path(theme_id): axum::extract::Path<String>,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    state.theme_manager().delete_theme(&theme_id).await?;
-    Ok(no_content())
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can view responsive image settings",
+        ));
+    }
+
+    Ok(json(
+        state.responsive_images.config().await.as_ref().clone(),
+    ))
 }
 
-/// Activate a theme
-async fn activate_theme_handler(
+/// Update the render-time image loading and srcset settings
+async fn update_responsive_images_config_handler(
     user: AuthUser,
-    axum::extract::Path(theme_id): axum::extract::Path<String>,
     State(state): State<AppState>,
+    Json(config): Json<crate::services::ResponsiveImagesConfig>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let theme = state.theme_manager().activate_theme(&theme_id).await?;
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can change responsive image settings",
+        ));
+    }
 
-    Ok(json(serde_json::json!({
-        "success": true,
-        "message": format!("Theme '{}' activated successfully", theme.name),
-        "theme": theme
-    })))
+    let config = state.responsive_images.update_config(config).await?;
+    // Cached pages were post-processed under the old settings
+    if let Err(e) = state.page_cache.purge_all().await {
+        tracing::warn!("Failed to purge cached pages: {}", e);
+    }
+    Ok(json(config))
 }
 
-/// Get theme settings and customizer schema
-async fn get_theme_settings_handler(
-    axum::extract::Path(theme_id): axum::extract::Path<String>,
+/// A media library image at a registered size. The version is generated
+/// and stored on first request; the response redirects to it
+async fn media_transform_handler(
+    axum::extract::Path((id, size)): axum::extract::Path<(Uuid, String)>,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let settings = state.theme_manager().get_theme_settings(&theme_id).await?;
-    Ok(json(settings))
+    let url = state.responsive_images.transform(id, &size).await?;
+    Ok(axum::response::Redirect::temporary(&url))
 }
 
-/// Update theme settings/customizations
-async fn update_theme_settings_handler(
+// =============================================================================
+// Comment Handlers
+// =============================================================================
+
+use rustpress_api::services::comment_service::{
+    BatchModerateRequest, CommentResponse, CommentService,
+    CreateCommentRequest as CommentCreateRequest, UpdateCommentRequest as CommentUpdateRequest,
+    UpdatePostCommentSettingsRequest,
+};
+use rustpress_database::repository::comments::CommentStatus;
+use rustpress_events::DomainEvent;
+
+/// Comment list query parameters
+#[derive(Debug, serde::Deserialize)]
+struct CommentListQuery {
+    page: Option<u32>,
+    per_page: Option<u32>,
+    post_id: Option<Uuid>,
+    status: Option<String>,
+    search: Option<String>,
+    filter: Option<String>,
+}
+
+async fn list_comments_handler(
     user: AuthUser,
-    axum::extract::Path(theme_id): axum::extract::Path<String>,
+    Query(query): Query<CommentListQuery>,
     State(state): State<AppState>,
-    Json(payload): Json<serde_json::Value>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let settings = state
-        .theme_manager()
-        .update_theme_settings(&theme_id, payload)
+    let service = CommentService::new(state.db().inner().clone());
+
+    let status = query.status.and_then(|s| s.parse::<CommentStatus>().ok());
+
+    let result = service
+        .list_comments(
+            query.page.unwrap_or(1).into(),
+            query.per_page.unwrap_or(20).into(),
+            query.post_id,
+            status,
+            query.search,
+            query.filter,
+        )
         .await?;
 
-    Ok(json(serde_json::json!({
-        "success": true,
-        "settings": settings
-    })))
+    Ok(json(result))
 }
 
-/// Get menu assignments for a theme
-async fn get_theme_menus_handler(
-    axum::extract::Path(theme_id): axum::extract::Path<String>,
+async fn create_comment_handler(
+    user: Option<AuthUser>,
     State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<std::net::SocketAddr>,
+    headers: axum::http::HeaderMap,
+    Json(body): Json<serde_json::Value>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let menus = state
-        .theme_manager()
-        .get_menu_assignments(&theme_id)
+    // Signed-in users are trusted; anonymous comments go through the challenges
+    let mut payload: CommentCreateRequest = if user.is_some() {
+        serde_json::from_value(body)
+            .map_err(|e| HttpError::unprocessable_entity(format!("Invalid comment: {}", e)))?
+    } else {
+        challenged_body(
+            &state,
+            crate::services::abuse_challenge::COMMENT_ENDPOINT,
+            body,
+        )
+        .await?
+    };
+    let service = CommentService::new(state.db().inner().clone());
+
+    let user_id = user.as_ref().map(|u| u.id);
+    let ip = Some(addr.ip().to_string());
+    let user_agent = headers
+        .get(axum::http::header::USER_AGENT)
+        .and_then(|v| v.to_str().ok())
+        .map(String::from);
+
+    let sanitized = state.sanitization.sanitize_comment(&payload.content).await;
+    payload.content = sanitized.html.clone();
+
+    let comment = service
+        .submit_comment(payload, user_id, ip, user_agent)
         .await?;
-    Ok(json(menus))
+    state
+        .sanitization
+        .record_quietly(
+            ContentSource::Comment,
+            Some(comment.id),
+            user_id,
+            &sanitized,
+        )
+        .await;
+    state
+        .publish(comment_event(user.as_ref(), "comment.created", &comment))
+        .await;
+
+    Ok(created(comment))
 }
 
-/// Update menu assignments for a theme
-async fn update_theme_menus_handler(
+/// Domain event scoped to the tenant of the acting user, if any
+fn user_event(
+    user: Option<&AuthUser>,
+    event_type: &str,
+    payload: serde_json::Value,
+) -> DomainEvent {
+    let event = DomainEvent::new(event_type, payload);
+    match user
+        .and_then(|u| u.claims.tenant_id.as_deref())
+        .and_then(|t| Uuid::parse_str(t).ok())
+    {
+        Some(tenant_id) => event.with_tenant(tenant_id),
+        None => event,
+    }
+}
+
+/// Comment event for live moderation views
+fn comment_event(
+    user: Option<&AuthUser>,
+    event_type: &str,
+    comment: &CommentResponse,
+) -> DomainEvent {
+    user_event(
+        user,
+        event_type,
+        serde_json::json!({
+            "id": comment.id,
+            "post_id": comment.post_id,
+            "parent_id": comment.parent_id,
+            "status": comment.status,
+            "author_id": comment.author.id,
+            "author_name": comment.author.name,
+            "created_at": comment.created_at,
+        }),
+    )
+    .with_aggregate(comment.id, "comment")
+}
+
+async fn get_comment_handler(
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let service = CommentService::new(state.db().inner().clone());
+
+    match service.get_comment(id).await? {
+        Some(comment) => Ok(json(comment)),
+        None => Err(rustpress_core::error::Error::not_found("Comment", id.to_string()).into()),
+    }
+}
+
+async fn update_comment_handler(
     user: AuthUser,
-    axum::extract::Path(theme_id): axum::extract::Path<String>,
+    PathId(id): PathId,
     State(state): State<AppState>,
-    Json(payload): Json<serde_json::Value>,
+    Json(payload): Json<CommentUpdateRequest>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
+    let service = CommentService::new(state.db().inner().clone());
+    let comment = service.update_comment(id, payload).await?;
     state
-        .theme_manager()
-        .update_menu_assignments(&theme_id, payload)
-        .await?;
-
-    Ok(json(serde_json::json!({
-        "success": true,
-        "message": "Menu assignments updated"
-    })))
+        .publish(comment_event(Some(&user), "comment.updated", &comment))
+        .await;
+    Ok(json(comment))
 }
 
-/// Get widget assignments for a theme
-async fn get_theme_widgets_handler(
-    axum::extract::Path(theme_id): axum::extract::Path<String>,
+async fn delete_comment_handler(
+    user: AuthUser,
+    PathId(id): PathId,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let widgets = state
-        .theme_manager()
-        .get_widget_assignments(&theme_id)
-        .await?;
-    Ok(json(widgets))
+    let service = CommentService::new(state.db().inner().clone());
+    let comment = service.get_comment(id).await?;
+    if service.delete_comment(id).await? {
+        if let Some(comment) = comment {
+            state
+                .publish(comment_event(Some(&user), "comment.deleted", &comment))
+                .await;
+        }
+    }
+    Ok(no_content())
 }
 
-/// Update widget assignments for a theme
-async fn update_theme_widgets_handler(
+async fn approve_comment_handler(
     user: AuthUser,
-    axum::extract::Path(theme_id): axum::extract::Path<String>,
+    PathId(id): PathId,
     State(state): State<AppState>,
-    Json(payload): Json<serde_json::Value>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
+    let service = CommentService::new(state.db().inner().clone());
+    let comment = service.approve_comment(id, user.id).await?;
     state
-        .theme_manager()
-        .update_widget_assignments(&theme_id, payload)
-        .await?;
-
-    Ok(json(serde_json::json!({
-        "success": true,
-        "message": "Widget assignments updated"
-    })))
+        .publish(comment_event(Some(&user), "comment.approved", &comment))
+        .await;
+    Ok(json(comment))
 }
 
-/// Create a theme preview session
-async fn create_theme_preview_handler(
+async fn mark_spam_handler(
     user: AuthUser,
-    axum::extract::Path(theme_id): axum::extract::Path<String>,
+    PathId(id): PathId,
     State(state): State<AppState>,
-    Json(payload): Json<serde_json::Value>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let preview = state
-        .theme_manager()
-        .create_preview(user.id, &theme_id, payload)
-        .await?;
-
-    Ok(json(serde_json::json!({
-        "preview_token": preview.token,
-        "preview_url": format!("/?preview={}", preview.token),
-        "expires_at": preview.expires_at
-    })))
+    let service = CommentService::new(state.db().inner().clone());
+    let comment = service.mark_as_spam(id, user.id).await?;
+    state
+        .publish(comment_event(Some(&user), "comment.spam", &comment))
+        .await;
+    Ok(json(comment))
 }
 
-/// Upload and install a theme from ZIP file
-async fn upload_theme_handler(
+async fn trash_comment_handler(
     user: AuthUser,
+    PathId(id): PathId,
     State(state): State<AppState>,
-    mut multipart: axum::extract::Multipart,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    // Extract ZIP file from multipart
-    let mut zip_data: Option<Vec<u8>> = None;
-    let mut activate_after = false;
-
-    while let Some(field) = multipart.next_field().await.map_err(|e| {
-        crate::error::HttpError::bad_request(format!("Failed to read multipart: {}", e))
-    })? {
-        let name = field.name().unwrap_or("").to_string();
+    let service = CommentService::new(state.db().inner().clone());
+    let comment = service.trash_comment(id, user.id).await?;
+    state
+        .publish(comment_event(Some(&user), "comment.trashed", &comment))
+        .await;
+    Ok(json(comment))
+}
 
-        if name == "theme" || name == "file" {
-            zip_data = Some(
-                field
-                    .bytes()
-                    .await
-                    .map_err(|e| {
-                        crate::error::HttpError::bad_request(format!("Failed to read file: {}", e))
-                    })?
-                    .to_vec(),
-            );
-        } else if name == "activate" {
-            let value = field.text().await.unwrap_or_default();
-            activate_after = value == "true" || value == "1";
-        }
+async fn batch_moderate_comments_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(payload): Json<BatchModerateRequest>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let service = CommentService::new(state.db().inner().clone());
+    let comment_ids = payload.comment_ids.clone();
+    let status = payload.status;
+    let updated = service.batch_moderate(payload, user.id).await?;
+    if updated > 0 {
+        state
+            .publish(user_event(
+                Some(&user),
+                "comment.batch_moderated",
+                serde_json::json!({
+                    "comment_ids": comment_ids,
+                    "status": status,
+                    "updated": updated,
+                }),
+            ))
+            .await;
     }
+    Ok(json(serde_json::json!({ "updated": updated })))
+}
 
-    let zip_data = zip_data.ok_or_else(|| {
-        crate::error::HttpError::bad_request("No theme file provided".to_string())
-    })?;
+async fn toggle_like_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let service = CommentService::new(state.db().inner().clone());
+    let liked = service.toggle_like(id, user.id).await?;
+    Ok(json(serde_json::json!({ "liked": liked })))
+}
 
-    // Install the theme
-    let result = state
-        .theme_manager()
-        .install_from_zip(&zip_data, activate_after)
+async fn comment_counts_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let service = CommentService::new(state.db().inner().clone());
+    let counts = service.get_counts().await?;
+    Ok(json(counts))
+}
+
+/// Moderation queue query parameters
+#[derive(Debug, serde::Deserialize)]
+struct CommentQueueQuery {
+    page: Option<u32>,
+    per_page: Option<u32>,
+    /// `pending` (default) or `spam`
+    status: Option<String>,
+}
+
+async fn comment_queue_handler(
+    _user: AuthUser,
+    Query(query): Query<CommentQueueQuery>,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let status = match query.status {
+        Some(status) => status
+            .parse::<CommentStatus>()
+            .map_err(|e| rustpress_core::error::Error::invalid_input("status", e))?,
+        None => CommentStatus::Pending,
+    };
+    let service = CommentService::new(state.db().inner().clone());
+    let queue = service
+        .moderation_queue(
+            status,
+            query.page.unwrap_or(1).into(),
+            query.per_page.unwrap_or(20).into(),
+        )
         .await?;
+    Ok(json(queue))
+}
 
-    Ok(created(serde_json::json!({
-        "success": result.success,
-        "theme_id": result.theme_id,
-        "theme_name": result.theme_name,
-        "message": result.message,
-        "warnings": result.warnings
+/// Approved comments of a post, threaded
+async fn post_comments_handler(
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let service = CommentService::new(state.db().inner().clone());
+    let settings = service.get_post_settings(id).await?;
+    let comments = service.get_comment_tree(id).await?;
+    Ok(json(serde_json::json!({
+        "post_id": id,
+        "comments_open": settings.comments_open,
+        "comments": comments,
     })))
 }
 
-/// Validate a theme ZIP without installing
-async fn validate_theme_handler(
-    user: AuthUser,
+async fn get_post_comment_settings_handler(
+    _user: AuthUser,
+    PathId(id): PathId,
     State(state): State<AppState>,
-    mut multipart: axum::extract::Multipart,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let mut zip_data: Option<Vec<u8>> = None;
+    let service = CommentService::new(state.db().inner().clone());
+    let settings = service.get_post_settings(id).await?;
+    Ok(json(settings))
+}
 
-    while let Some(field) = multipart.next_field().await.map_err(|e| {
-        crate::error::HttpError::bad_request(format!("Failed to read multipart: {}", e))
-    })? {
-        let name = field.name().unwrap_or("").to_string();
+async fn update_post_comment_settings_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+    Json(payload): Json<UpdatePostCommentSettingsRequest>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let service = CommentService::new(state.db().inner().clone());
+    let settings = service.update_post_settings(id, payload).await?;
+    state
+        .publish(
+            user_event(
+                Some(&user),
+                "post.comment_settings_updated",
+                serde_json::to_value(&settings).unwrap_or_default(),
+            )
+            .with_aggregate(id, "post"),
+        )
+        .await;
+    Ok(json(settings))
+}
 
-        if name == "theme" || name == "file" {
-            zip_data = Some(
-                field
-                    .bytes()
-                    .await
-                    .map_err(|e| {
-                        crate::error::HttpError::bad_request(format!("Failed to read file: {}", e))
-                    })?
-                    .to_vec(),
-            );
-        }
-    }
+// =============================================================================
+// Settings Handlers
+// =============================================================================
 
-    let zip_data = zip_data.ok_or_else(|| {
-        crate::error::HttpError::bad_request("No theme file provided".to_string())
-    })?;
+use crate::response::Accepted;
+use crate::services::{ChangeRequest, ChangeRequestStatus, SettingApprovalService};
+use rustpress_api::services::settings_service::{
+    BatchUpdateRequest, SettingUpdate, SettingsService,
+};
 
-    let result = state.theme_manager().validate_zip(&zip_data)?;
+/// Response to a write that needs another administrator's approval
+fn pending_approval(request: ChangeRequest) -> Response {
+    Accepted::new("The change is waiting for approval by another administrator")
+        .with_status_url(format!("/api/v1/settings/change-requests/{}", request.id))
+        .with_data(request)
+        .into_response()
+}
 
-    Ok(json(serde_json::json!({
-        "valid": result.valid,
-        "theme_id": result.theme_id,
-        "theme_name": result.theme_name,
-        "errors": result.errors,
-        "warnings": result.warnings
-    })))
+/// List all settings grouped
+async fn list_settings_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let service = SettingsService::new(state.db().inner().clone());
+    let settings = service.get_all_grouped().await?;
+    Ok(json(settings))
 }
 
-/// Get available default themes
-async fn get_available_themes_handler(
+/// Get a single setting by key
+async fn get_setting_handler(
+    user: AuthUser,
+    axum::extract::Path(key): axum::extract::Path<String>,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let available = state.theme_manager().get_available_themes();
-    Ok(json(serde_json::json!({ "themes": available })))
+    let service = SettingsService::new(state.db().inner().clone());
+    match service.get(&key).await? {
+        Some(setting) => {
+            let version = setting.version;
+            Ok(versioned(setting, version))
+        }
+        None => Err(crate::error::HttpError::not_found("Setting not found")),
+    }
 }
 
-/// Update a theme from ZIP file
-async fn update_theme_handler(
+/// Update a single setting
+async fn update_setting_handler(
     user: AuthUser,
-    axum::extract::Path(theme_id): axum::extract::Path<String>,
+    axum::extract::Path(key): axum::extract::Path<String>,
     State(state): State<AppState>,
-    mut multipart: axum::extract::Multipart,
+    IfMatch(if_match): IfMatch,
+    Json(payload): Json<serde_json::Value>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let mut zip_data: Option<Vec<u8>> = None;
+    let service = SettingsService::new(state.db().inner().clone());
 
-    while let Some(field) = multipart.next_field().await.map_err(|e| {
-        crate::error::HttpError::bad_request(format!("Failed to read multipart: {}", e))
-    })? {
-        let name = field.name().unwrap_or("").to_string();
+    // Extract value from payload (support both { "value": x } and direct value)
+    let reason = payload
+        .get("reason")
+        .and_then(|r| r.as_str())
+        .map(String::from);
+    let value = if let Some(v) = payload.get("value") {
+        v.clone()
+    } else {
+        payload
+    };
 
-        if name == "theme" || name == "file" {
-            zip_data = Some(
-                field
-                    .bytes()
-                    .await
-                    .map_err(|e| {
-                        crate::error::HttpError::bad_request(format!("Failed to read file: {}", e))
-                    })?
-                    .to_vec(),
-            );
-        }
+    let approvals = SettingApprovalService::new(state.db().inner().clone());
+    let update = SettingUpdate {
+        key: key.clone(),
+        value,
+        version: if_match,
+    };
+    if let Some(changes) = approvals.review(std::slice::from_ref(&update)).await? {
+        let request = approvals.request(changes, user.id, reason).await?;
+        return Ok(pending_approval(request));
     }
 
-    let zip_data = zip_data.ok_or_else(|| {
-        crate::error::HttpError::bad_request("No theme file provided".to_string())
-    })?;
+    let updated = service
+        .update_versioned(&key, update.value, if_match)
+        .await?;
+    let version = updated.version;
+    Ok(versioned(updated, version).into_response())
+}
 
-    let result = state.theme_manager().update_from_zip(&zip_data).await?;
+/// Batch update multiple settings
+async fn batch_update_settings_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(payload): Json<BatchUpdateRequest>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let approvals = SettingApprovalService::new(state.db().inner().clone());
+    if let Some(changes) = approvals.review(&payload.settings).await? {
+        let request = approvals.request(changes, user.id, payload.reason).await?;
+        return Ok(pending_approval(request));
+    }
 
+    let service = SettingsService::new(state.db().inner().clone());
+    let updated = service.batch_update(payload.settings).await?;
     Ok(json(serde_json::json!({
-        "success": result.success,
-        "theme_id": result.theme_id,
-        "theme_name": result.theme_name,
-        "message": result.message,
-        "warnings": result.warnings
-    })))
+        "updated": updated.len(),
+        "settings": updated
+    }))
+    .into_response())
 }
 
-/// Export a theme as ZIP file
-async fn export_theme_handler(
+/// Get settings by group
+async fn get_settings_group_handler(
     user: AuthUser,
-    axum::extract::Path(theme_id): axum::extract::Path<String>,
+    axum::extract::Path(group): axum::extract::Path<String>,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let zip_data = state.theme_manager().export_theme(&theme_id).await?;
-    let disposition = format!("attachment; filename=\"{}.zip\"", theme_id);
-
-    Ok(axum::response::Response::builder()
-        .status(axum::http::StatusCode::OK)
-        .header(axum::http::header::CONTENT_TYPE, "application/zip")
-        .header(axum::http::header::CONTENT_DISPOSITION, disposition)
-        .body(axum::body::Body::from(zip_data))
-        .unwrap())
+    let service = SettingsService::new(state.db().inner().clone());
+    let settings = service.get_by_group(&group).await?;
+    Ok(json(settings))
 }
 
-// =============================================================================
-// Public Website Handlers (Theme Rendering)
-// =============================================================================
+/// Get general settings
+async fn get_general_settings_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let service = SettingsService::new(state.db().inner().clone());
+    let settings = service.get_by_group("general").await?;
+    Ok(json(settings))
+}
 
-/// Query params for public routes
-#[derive(Debug, Deserialize)]
-struct PublicQueryParams {
-    page: Option<i32>,
-    preview: Option<String>,
+/// Get reading settings
+async fn get_reading_settings_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let service = SettingsService::new(state.db().inner().clone());
+    let settings = service.get_by_group("reading").await?;
+    Ok(json(settings))
 }
 
-/// Convert rendered page to response
-fn rendered_response(
-    result: Result<crate::services::RenderedPage, rustpress_core::error::Error>,
-) -> Response {
-    match result {
-        Ok(page) => {
-            let mut response = Html(page.html).into_response();
-            let headers = response.headers_mut();
-            headers.insert(
-                header::CACHE_CONTROL,
-                page.cache_control
-                    .parse()
-                    .unwrap_or_else(|_| "no-cache".parse().unwrap()),
-            );
-            headers.insert(
-                header::CONTENT_TYPE,
-                page.content_type
-                    .parse()
-                    .unwrap_or_else(|_| "text/html".parse().unwrap()),
-            );
-            response
-        }
-        Err(e) => {
-            let status = if e.to_string().contains("not found") {
-                axum::http::StatusCode::NOT_FOUND
-            } else {
-                axum::http::StatusCode::INTERNAL_SERVER_ERROR
-            };
-            (
-                status,
-                Html(format!(
-                    r#"<!DOCTYPE html>
-<html>
-<head><title>Error</title></head>
-<body>
-<h1>Error</h1>
-<p>{}</p>
-</body>
-</html>"#,
-                    e
-                )),
-            )
-                .into_response()
-        }
-    }
+/// Get writing settings
+async fn get_writing_settings_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let service = SettingsService::new(state.db().inner().clone());
+    let settings = service.get_by_group("writing").await?;
+    Ok(json(settings))
 }
 
-/// Public home page handler
-async fn public_home_handler(
+/// Get discussion settings
+async fn get_discussion_settings_handler(
+    user: AuthUser,
     State(state): State<AppState>,
-    Query(params): Query<PublicQueryParams>,
-) -> Response {
-    let result = state
-        .renderer()
-        .render_home(params.preview.as_deref())
-        .await;
-    rendered_response(result)
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let service = SettingsService::new(state.db().inner().clone());
+    let settings = service.get_by_group("discussion").await?;
+    Ok(json(settings))
 }
 
-/// Public blog archive handler
-async fn public_blog_handler(
+/// Get permalinks settings
+async fn get_permalinks_settings_handler(
+    user: AuthUser,
     State(state): State<AppState>,
-    Query(params): Query<PublicQueryParams>,
-) -> Response {
-    // Blog page is same as home for now
-    let result = state
-        .renderer()
-        .render_home(params.preview.as_deref())
-        .await;
-    rendered_response(result)
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let service = SettingsService::new(state.db().inner().clone());
+    let settings = service.get_by_group("permalinks").await?;
+    Ok(json(settings))
 }
 
-/// Public single post handler
-async fn public_post_handler(
-    State(state): State<AppState>,
-    axum::extract::Path(slug): axum::extract::Path<String>,
-    Query(params): Query<PublicQueryParams>,
-) -> Response {
-    let result = state
-        .renderer()
-        .render_post(&slug, params.preview.as_deref())
-        .await;
-    rendered_response(result)
+#[derive(Debug, Deserialize)]
+struct SettingChangeRequestQuery {
+    #[serde(default)]
+    status: Option<String>,
 }
 
-/// Public page handler
-async fn public_page_handler(
-    State(state): State<AppState>,
-    axum::extract::Path(slug): axum::extract::Path<String>,
-    Query(params): Query<PublicQueryParams>,
-) -> Response {
-    let result = state
-        .renderer()
-        .render_page(&slug, params.preview.as_deref())
-        .await;
-    rendered_response(result)
+#[derive(Debug, Default, Deserialize)]
+struct ReviewSettingChangeRequest {
+    #[serde(default)]
+    note: Option<String>,
 }
 
-/// Public category archive handler
-async fn public_category_handler(
+/// List setting change requests, optionally by status
+async fn list_setting_change_requests_handler(
+    user: AuthUser,
     State(state): State<AppState>,
-    axum::extract::Path(slug): axum::extract::Path<String>,
-    Query(params): Query<PublicQueryParams>,
-) -> Response {
-    let page = params.page.unwrap_or(1);
-    let result = state
-        .renderer()
-        .render_category(&slug, page, params.preview.as_deref())
-        .await;
-    rendered_response(result)
+    Query(query): Query<SettingChangeRequestQuery>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can review setting changes",
+        ));
+    }
+    let status = query
+        .status
+        .as_deref()
+        .map(str::parse::<ChangeRequestStatus>)
+        .transpose()?;
+
+    let service = SettingApprovalService::new(state.db().inner().clone());
+    let requests = service.list(status).await?;
+    Ok(json(serde_json::json!({ "requests": requests })))
 }
 
-/// Public tag archive handler
-async fn public_tag_handler(
+/// Get a setting change request with its diff and audit trail
+async fn get_setting_change_request_handler(
+    user: AuthUser,
+    PathId(id): PathId,
     State(state): State<AppState>,
-    axum::extract::Path(slug): axum::extract::Path<String>,
-    Query(params): Query<PublicQueryParams>,
-) -> Response {
-    let page = params.page.unwrap_or(1);
-    let result = state
-        .renderer()
-        .render_tag(&slug, page, params.preview.as_deref())
-        .await;
-    rendered_response(result)
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can review setting changes",
+        ));
+    }
+    let service = SettingApprovalService::new(state.db().inner().clone());
+    let request = service.get(id).await?;
+    let history = service.history(id).await?;
+    Ok(json(serde_json::json!({
+        "request": request,
+        "history": history
+    })))
 }
 
-/// Public author archive handler
-async fn public_author_handler(
+/// Approve a setting change request, applying its changes
+async fn approve_setting_change_request_handler(
+    user: AuthUser,
+    PathId(id): PathId,
     State(state): State<AppState>,
-    axum::extract::Path(slug): axum::extract::Path<String>,
-    Query(params): Query<PublicQueryParams>,
-) -> Response {
-    let page = params.page.unwrap_or(1);
-    let result = state
-        .renderer()
-        .render_author(&slug, page, params.preview.as_deref())
-        .await;
-    rendered_response(result)
+    payload: Option<Json<ReviewSettingChangeRequest>>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can approve setting changes",
+        ));
+    }
+    let note = payload.and_then(|Json(p)| p.note);
+    let service = SettingApprovalService::new(state.db().inner().clone());
+    let (request, settings) = service.approve(id, user.id, note).await?;
+    Ok(json(serde_json::json!({
+        "request": request,
+        "settings": settings
+    })))
 }
 
-/// Search query params
-#[derive(Debug, Deserialize)]
-struct SearchQueryParams {
-    q: Option<String>,
-    page: Option<i32>,
-    preview: Option<String>,
+/// Reject a setting change request
+async fn reject_setting_change_request_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+    payload: Option<Json<ReviewSettingChangeRequest>>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can reject setting changes",
+        ));
+    }
+    let note = payload.and_then(|Json(p)| p.note);
+    let service = SettingApprovalService::new(state.db().inner().clone());
+    Ok(json(service.reject(id, user.id, note).await?))
 }
 
-/// Public search results handler
-async fn public_search_handler(
+/// Withdraw one's own setting change request
+async fn cancel_setting_change_request_handler(
+    user: AuthUser,
+    PathId(id): PathId,
     State(state): State<AppState>,
-    Query(params): Query<SearchQueryParams>,
-) -> Response {
-    let query = params.q.unwrap_or_default();
-    let page = params.page.unwrap_or(1);
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let service = SettingApprovalService::new(state.db().inner().clone());
+    Ok(json(service.cancel(id, user.id).await?))
+}
 
-    if query.is_empty() {
-        return Html(
-            r#"<!DOCTYPE html>
-<html>
-<head><title>Search</title></head>
-<body>
-<h1>Search</h1>
-<form action="/search" method="get">
-<input type="search" name="q" placeholder="Search...">
-<button type="submit">Search</button>
-</form>
-</body>
-</html>"#,
-        )
-        .into_response();
-    }
+// =============================================================================
+// Storage Configuration Handlers
+// =============================================================================
 
-    let result = state
-        .renderer()
-        .render_search(&query, page, params.preview.as_deref())
-        .await;
-    rendered_response(result)
+use rustpress_api::services::storage_service::{
+    MigrationRequest, ProviderConfig, StorageCategory, StorageConfigRequest, StorageProvider,
+    StorageService as StorageConfigService,
+};
+
+/// List all storage configurations
+async fn list_storage_configurations_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let service = StorageConfigService::new(state.db().inner().clone());
+    let configurations = service.get_all_configurations().await?;
+    Ok(json(
+        serde_json::json!({ "configurations": configurations }),
+    ))
 }
 
-/// Public RSS feed handler
-async fn public_feed_handler(State(state): State<AppState>) -> impl IntoResponse {
-    // Generate RSS 2.0 feed
-    let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
-<rss version="2.0" xmlns:atom="http://www.w3.org/2005/Atom">
-<channel>
-    <title>RustPress Site</title>
-    <link>http://localhost:3000</link>
-    <description>Powered by RustPress</description>
-    <language>en-US</language>
-    <lastBuildDate>Mon, 15 Dec 2025 00:00:00 +0000</lastBuildDate>
-    <atom:link href="http://localhost:3000/feed" rel="self" type="application/rss+xml"/>
-</channel>
-</rss>"#;
+/// Get storage configuration for a specific category
+async fn get_storage_configuration_handler(
+    user: AuthUser,
+    axum::extract::Path(category): axum::extract::Path<String>,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let category: StorageCategory = category.parse().map_err(|e| {
+        rustpress_core::error::Error::validation(format!("Invalid category: {}", category))
+    })?;
 
-    (
-        [(header::CONTENT_TYPE, "application/rss+xml; charset=utf-8")],
-        xml,
-    )
+    let service = StorageConfigService::new(state.db().inner().clone());
+    let configuration = service.get_or_create_default(&category).await?;
+    Ok(json(configuration))
 }
 
-/// Public Atom feed handler
-async fn public_atom_feed_handler(State(state): State<AppState>) -> impl IntoResponse {
-    // Generate Atom feed
-    let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
-<feed xmlns="http://www.w3.org/2005/Atom">
-    <title>RustPress Site</title>
-    <link href="http://localhost:3000"/>
-    <link href="http://localhost:3000/feed/atom" rel="self"/>
-    <id>http://localhost:3000/</id>
-    <updated>2025-12-15T00:00:00Z</updated>
-</feed>"#;
+/// Update storage configuration for a category
+async fn update_storage_configuration_handler(
+    user: AuthUser,
+    axum::extract::Path(category): axum::extract::Path<String>,
+    State(state): State<AppState>,
+    Json(request): Json<StorageConfigRequest>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let category: StorageCategory = category.parse().map_err(|e| {
+        rustpress_core::error::Error::validation(format!("Invalid category: {}", category))
+    })?;
 
-    (
-        [(header::CONTENT_TYPE, "application/atom+xml; charset=utf-8")],
-        xml,
-    )
+    let service = StorageConfigService::new(state.db().inner().clone());
+    let configuration = service.update_configuration(&category, request).await?;
+    Ok(json(serde_json::json!({
+        "message": "Storage configuration updated",
+        "configuration": configuration
+    })))
 }
 
-/// Public sitemap handler
-async fn public_sitemap_handler(State(state): State<AppState>) -> impl IntoResponse {
-    // Generate XML sitemap
-    let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
-<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
-    <url>
-        <loc>http://localhost:3000/</loc>
-        <lastmod>2025-12-15</lastmod>
-        <changefreq>daily</changefreq>
-        <priority>1.0</priority>
-    </url>
-    <url>
-        <loc>http://localhost:3000/blog</loc>
-        <lastmod>2025-12-15</lastmod>
-        <changefreq>daily</changefreq>
-        <priority>0.8</priority>
-    </url>
-</urlset>"#;
-
-    (
-        [(header::CONTENT_TYPE, "application/xml; charset=utf-8")],
-        xml,
-    )
+/// Test storage connection request
+#[derive(Debug, Deserialize)]
+struct TestConnectionRequest {
+    provider: StorageProvider,
+    config: ProviderConfig,
 }
 
-/// Public robots.txt handler
-async fn public_robots_handler() -> impl IntoResponse {
-    let robots = r#"User-agent: *
-Allow: /
-
-Sitemap: http://localhost:3000/sitemap.xml"#;
-
-    (
-        [(header::CONTENT_TYPE, "text/plain; charset=utf-8")],
-        robots,
-    )
+/// Test storage connection
+async fn test_storage_connection_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(request): Json<TestConnectionRequest>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let service = StorageConfigService::new(state.db().inner().clone());
+    let result = service
+        .test_connection(&request.provider, &request.config)
+        .await?;
+    Ok(json(result))
 }
 
-/// Theme static asset handler
-async fn theme_asset_handler(
+/// Start a migration job
+async fn start_migration_handler(
+    user: AuthUser,
     State(state): State<AppState>,
-    axum::extract::Path((theme_id, path)): axum::extract::Path<(String, String)>,
-) -> Response {
-    use tokio::fs;
-
-    // Build the file path - get themes_dir from theme manager
-    let themes_dir = state.theme_manager().themes_dir().to_path_buf();
-    let file_path = themes_dir.join(&theme_id).join(&path);
+    Json(request): Json<MigrationRequest>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let service = StorageConfigService::new(state.db().inner().clone());
+    let status = service.start_migration(request).await?;
+    Ok(created(status))
+}
 
-    // Security: ensure path doesn't escape themes directory
-    let canonical = match file_path.canonicalize() {
-        Ok(p) => p,
-        Err(_) => {
-            return (axum::http::StatusCode::NOT_FOUND, "File not found").into_response();
-        }
-    };
+/// Get migration status
+async fn get_migration_status_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let service = StorageConfigService::new(state.db().inner().clone());
+    let status = service.get_migration_status(id).await?;
 
-    let themes_canonical = themes_dir.canonicalize().unwrap_or(themes_dir);
-    if !canonical.starts_with(&themes_canonical) {
-        return (axum::http::StatusCode::FORBIDDEN, "Access denied").into_response();
+    match status {
+        Some(s) => Ok(json(s)),
+        None => Err(HttpError::not_found("Migration not found")),
     }
+}
 
-    // Read file
-    match fs::read(&canonical).await {
-        Ok(contents) => {
-            // Determine content type
-            let content_type = match file_path.extension().and_then(|e| e.to_str()) {
-                Some("css") => "text/css",
-                Some("js") => "application/javascript",
-                Some("png") => "image/png",
-                Some("jpg") | Some("jpeg") => "image/jpeg",
-                Some("gif") => "image/gif",
-                Some("svg") => "image/svg+xml",
-                Some("woff") => "font/woff",
-                Some("woff2") => "font/woff2",
-                Some("ttf") => "font/ttf",
-                Some("eot") => "application/vnd.ms-fontobject",
-                Some("ico") => "image/x-icon",
-                Some("webp") => "image/webp",
-                Some("json") => "application/json",
-                Some("xml") => "application/xml",
-                _ => "application/octet-stream",
-            };
+/// Cancel a running migration
+async fn cancel_migration_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let service = StorageConfigService::new(state.db().inner().clone());
+    let cancelled = service.cancel_migration(id).await?;
 
-            (
-                [
-                    (header::CONTENT_TYPE, content_type),
-                    (header::CACHE_CONTROL, "public, max-age=31536000"),
-                ],
-                contents,
-            )
-                .into_response()
-        }
-        Err(_) => (axum::http::StatusCode::NOT_FOUND, "File not found").into_response(),
+    if cancelled {
+        Ok(json(
+            serde_json::json!({ "message": "Migration cancelled" }),
+        ))
+    } else {
+        Err(HttpError::bad_request(
+            "Migration not found or already completed",
+        ))
     }
 }
 
 // =============================================================================
-// Search Routes and Handlers
+// Plugin Handlers (Placeholder implementations)
 // =============================================================================
 
-/// Search routes for full-text search
-fn search_routes() -> Router<AppState> {
-    Router::new()
-        .route("/", get(search_handler))
-        .route("/suggest", get(search_suggest_handler))
-        .route("/reindex", post(search_reindex_handler))
-        .route("/stats", get(search_stats_handler))
-}
-
-/// Search query parameters
-#[derive(Debug, Deserialize)]
-struct ApiSearchQuery {
-    q: String,
-    #[serde(rename = "type")]
-    content_type: Option<String>,
-    page: Option<u32>,
-    per_page: Option<u32>,
-}
-
-/// Full-text search handler
-async fn search_handler(
-    Query(query): Query<ApiSearchQuery>,
+async fn list_plugins_handler(
+    user: AuthUser,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
-    let page = query.page.unwrap_or(1);
-    let per_page = query.per_page.unwrap_or(20).min(100);
-    let offset = ((page - 1) * per_page) as i64;
-
-    // Build search query using PostgreSQL full-text search
-    let search_term = query.q.trim();
-    if search_term.is_empty() {
-        return Ok(json(serde_json::json!({
-            "results": [],
-            "total": 0,
-            "page": page,
-            "per_page": per_page
-        })));
-    }
-
-    // Convert search term to tsquery format
-    let ts_query = search_term
-        .split_whitespace()
-        .collect::<Vec<_>>()
-        .join(" & ");
-
-    // Search posts using full-text search
-    let posts: Vec<(Uuid, String, String, Option<String>, String, chrono::DateTime<chrono::Utc>)> = sqlx::query_as(
-        r#"
-        SELECT p.id, p.title, p.slug, p.excerpt, 'post' as content_type, p.published_at
-        FROM posts p
-        WHERE p.status = 'published'
-          AND p.deleted_at IS NULL
-          AND (
-            to_tsvector('english', COALESCE(p.title, '') || ' ' || COALESCE(p.content, '')) @@ to_tsquery('english', $1)
-            OR p.title ILIKE '%' || $2 || '%'
-            OR p.content ILIKE '%' || $2 || '%'
-          )
-        ORDER BY p.published_at DESC
-        LIMIT $3 OFFSET $4
-        "#
-    )
-    .bind(&ts_query)
-    .bind(search_term)
-    .bind(per_page as i64)
-    .bind(offset)
-    .fetch_all(pool)
-    .await
-    .map_err(|e| rustpress_core::error::Error::database_with_source("Search failed", e))?;
-
-    // Get total count
-    let total: (i64,) = sqlx::query_as(
-        r#"
-        SELECT COUNT(*)
-        FROM posts p
-        WHERE p.status = 'published'
-          AND p.deleted_at IS NULL
-          AND (
-            to_tsvector('english', COALESCE(p.title, '') || ' ' || COALESCE(p.content, '')) @@ to_tsquery('english', $1)
-            OR p.title ILIKE '%' || $2 || '%'
-            OR p.content ILIKE '%' || $2 || '%'
-          )
-        "#
-    )
-    .bind(&ts_query)
-    .bind(search_term)
-    .fetch_one(pool)
-    .await
-    .map_err(|e| rustpress_core::error::Error::database_with_source("Search count failed", e))?;
-
-    let results: Vec<serde_json::Value> = posts
+    let plugins = state.plugins.read().await;
+    let plugin_list: Vec<serde_json::Value> = plugins
+        .list()
         .iter()
-        .map(|(id, title, slug, excerpt, content_type, published_at)| {
+        .map(|info| {
             serde_json::json!({
-                "id": id,
-                "title": title,
-                "slug": slug,
-                "excerpt": excerpt,
-                "type": content_type,
-                "published_at": published_at
+                "id": info.id,
+                "name": info.name,
+                "version": info.version,
+                "description": info.description,
+                "author": info.author,
+                "active": plugins.state(&info.id) == Some(PluginState::Active)
             })
         })
         .collect();
+    Ok(json(serde_json::json!({ "plugins": plugin_list })))
+}
 
-    Ok(json(serde_json::json!({
-        "results": results,
-        "total": total.0,
-        "page": page,
-        "per_page": per_page,
-        "total_pages": (total.0 as f64 / per_page as f64).ceil() as i64
-    })))
+/// OpenAPI document of the API, including the routes plugins mount under
+/// `/plugins/{id}/`
+async fn openapi_handler(
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, HttpError> {
+    if !state.config.api.docs_enabled {
+        return Err(HttpError::not_found("API documentation is disabled"));
+    }
+    Ok(Json(state.plugin_routes.openapi_spec()))
 }
 
-/// Search suggestions handler
-async fn search_suggest_handler(
-    Query(query): Query<ApiSearchQuery>,
+async fn install_plugin_handler(
+    user: AuthUser,
     State(state): State<AppState>,
+    Json(payload): Json<serde_json::Value>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
-    let limit = query.per_page.unwrap_or(10).min(20);
-    let search_term = query.q.trim();
+    Ok(created(serde_json::json!({ "message": "Install plugin" })))
+}
 
-    if search_term.len() < 2 {
-        return Ok(json(serde_json::json!({ "suggestions": [] })));
+async fn get_plugin_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let plugins = state.plugins.read().await;
+    if let Some(info) = plugins.list().iter().find(|p| p.id == id.to_string()) {
+        Ok(json(serde_json::json!({
+            "id": info.id,
+            "name": info.name,
+            "version": info.version,
+            "description": info.description,
+            "author": info.author,
+            "active": plugins.state(&info.id) == Some(PluginState::Active),
+            "dependencies": info.dependencies,
+            "resources": plugins.resources(&info.id),
+            "error": plugins.error(&info.id)
+        })))
+    } else {
+        Err(crate::error::HttpError::not_found(format!(
+            "Plugin '{}' not found",
+            id
+        )))
     }
-
-    // Get title suggestions from posts
-    let suggestions: Vec<(String,)> = sqlx::query_as(
-        r#"
-        SELECT DISTINCT title
-        FROM posts
-        WHERE status = 'published'
-          AND deleted_at IS NULL
-          AND title ILIKE '%' || $1 || '%'
-        ORDER BY title
-        LIMIT $2
-        "#,
-    )
-    .bind(search_term)
-    .bind(limit as i64)
-    .fetch_all(pool)
-    .await
-    .map_err(|e| rustpress_core::error::Error::database_with_source("Suggestions failed", e))?;
-
-    let suggestions: Vec<String> = suggestions.into_iter().map(|(t,)| t).collect();
-
-    Ok(json(serde_json::json!({ "suggestions": suggestions })))
 }
 
-/// Trigger search reindex
-async fn search_reindex_handler(
+async fn uninstall_plugin_handler(
     user: AuthUser,
+    PathId(id): PathId,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    // In a real implementation, this would queue a background job
-    // to rebuild the full-text search index
-    Ok(json(serde_json::json!({
-        "status": "queued",
-        "message": "Search reindex job has been queued"
-    })))
+    Ok(no_content())
 }
 
-/// Get search statistics
-async fn search_stats_handler(
+async fn activate_plugin_handler(
     user: AuthUser,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    tenant: Option<axum::Extension<TenantId>>,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
-
-    // Get indexed content counts
-    let post_count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM posts WHERE post_type = 'post' AND status = 'published' AND deleted_at IS NULL")
-        .fetch_one(pool)
-        .await
-        .unwrap_or((0,));
+    check_extension_allowed(&state, tenant.as_deref(), ExtensionKind::Plugin, &id).await?;
 
-    let page_count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM posts WHERE post_type = 'page' AND status = 'published' AND deleted_at IS NULL")
-        .fetch_one(pool)
+    // Sites of a network activate plugins the server has loaded for
+    // themselves alone
+    if let Some(site) = current_site() {
+        if state.plugins.read().await.get(&id).is_none() {
+            return Err(HttpError::not_found(format!("Plugin '{}' not found", id)));
+        }
+        state
+            .plugins
+            .read()
+            .await
+            .check_compatibility(&id)?
+            .into_result()?;
+        state.sites.set_plugin(site, &id, true).await?;
+        return Ok(json(
+            serde_json::json!({ "id": id, "active": true, "site": site }),
+        ));
+    }
+
+    // Activate the plugin's dependencies along with it
+    let ctx = rustpress_core::context::AppContext::new(state.config().clone());
+    let activated = state
+        .plugins
+        .read()
         .await
-        .unwrap_or((0,));
+        .activate_with_dependencies(&id, &ctx)
+        .await?;
 
-    Ok(json(serde_json::json!({
-        "indexed_posts": post_count.0,
-        "indexed_pages": page_count.0,
-        "last_reindex": chrono::Utc::now(),
-        "index_health": "healthy"
-    })))
+    Ok(json(
+        serde_json::json!({ "id": id, "active": true, "activated": activated }),
+    ))
 }
 
-// =============================================================================
-// Backup Routes and Handlers
-// =============================================================================
+#[derive(Deserialize)]
+struct DeactivatePluginQuery {
+    /// Also deactivate the active plugins that require this one
+    #[serde(default)]
+    cascade: bool,
+}
 
-/// Backup management routes
-fn backup_routes() -> Router<AppState> {
-    Router::new()
-        .route("/", get(list_backups_handler).post(create_backup_handler))
-        .route("/storage", get(backup_storage_handler))
-        .route(
-            "/schedules",
-            get(list_backup_schedules_handler).post(create_backup_schedule_handler),
-        )
-        .route(
-            "/schedules/:id",
-            get(get_backup_schedule_handler)
-                .put(update_backup_schedule_handler)
-                .delete(delete_backup_schedule_handler),
-        )
-        .route(
-            "/:id",
-            get(get_backup_handler).delete(delete_backup_handler),
-        )
-        .route("/:id/download", get(download_backup_handler))
-        .route("/:id/restore", post(restore_backup_handler))
-        .route("/restore/:job_id", get(restore_progress_handler))
+async fn deactivate_plugin_handler(
+    user: AuthUser,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    Query(query): Query<DeactivatePluginQuery>,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if let Some(site) = current_site() {
+        state.sites.set_plugin(site, &id, false).await?;
+        return Ok(json(
+            serde_json::json!({ "id": id, "active": false, "site": site }),
+        ));
+    }
+
+    let ctx = rustpress_core::context::AppContext::new(state.config().clone());
+    let plugins = state.plugins.read().await;
+    let deactivated = if query.cascade {
+        plugins.deactivate_with_dependents(&id, &ctx).await?
+    } else {
+        plugins.deactivate(&id, &ctx).await?;
+        vec![id.clone()]
+    };
+
+    Ok(json(
+        serde_json::json!({ "id": id, "active": false, "deactivated": deactivated }),
+    ))
 }
 
-/// Backup list query
-#[derive(Debug, Deserialize)]
-struct BackupListQuery {
-    page: Option<u32>,
-    per_page: Option<u32>,
-    #[serde(rename = "type")]
-    backup_type: Option<String>,
+#[derive(Deserialize)]
+struct UpdatePluginSettingsRequest {
+    /// New settings, replacing the saved ones
+    settings: serde_json::Value,
+    /// Version the new settings are based on; 0 when none were saved yet
+    version: i64,
 }
 
-/// List all backups
-async fn list_backups_handler(
+/// Saved settings of a plugin, for the request's tenant or else the whole
+/// installation
+async fn get_plugin_settings_handler(
     user: AuthUser,
-    Query(query): Query<BackupListQuery>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    tenant: Option<axum::Extension<TenantId>>,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
-    let page = query.page.unwrap_or(1);
-    let per_page = query.per_page.unwrap_or(20);
-    let offset = ((page - 1) * per_page) as i64;
-
-    // Get backups from database (using actual schema with file_size column)
-    let backups: Vec<(
-        Uuid,
-        String,
-        String,
-        Option<i64>,
-        String,
-        chrono::DateTime<chrono::Utc>,
-    )> = sqlx::query_as(
-        r#"
-        SELECT id, name, backup_type, file_size, status, created_at
-        FROM backups
-        ORDER BY created_at DESC
-        LIMIT $1 OFFSET $2
-        "#,
-    )
-    .bind(per_page as i64)
-    .bind(offset)
-    .fetch_all(pool)
-    .await
-    .unwrap_or_default();
-
-    let total: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM backups")
-        .fetch_one(pool)
-        .await
-        .unwrap_or((0,));
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can view plugin settings",
+        ));
+    }
 
-    let backup_list: Vec<serde_json::Value> = backups
-        .iter()
-        .map(|(id, name, backup_type, size, status, created_at)| {
-            serde_json::json!({
-                "id": id,
-                "name": name,
-                "type": backup_type,
-                "size_bytes": size.unwrap_or(0),
-                "status": status,
-                "created_at": created_at
-            })
-        })
-        .collect();
+    let plugins = state.plugins.read().await;
+    let tenant_id = tenant.as_deref().map(|TenantId(tenant)| tenant.as_str());
+    let settings = plugins.plugin_settings(&id, tenant_id).await?;
+    let schema = plugins.get(&id).and_then(|plugin| plugin.config_schema());
 
     Ok(json(serde_json::json!({
-        "backups": backup_list,
-        "total": total.0,
-        "page": page,
-        "per_page": per_page
+        "settings": settings,
+        "schema": schema
     })))
 }
 
-/// Create backup request
-#[derive(Debug, Deserialize)]
-struct CreateBackupRequest {
-    #[serde(rename = "type")]
-    backup_type: Option<String>,
-    include_media: Option<bool>,
-    include_themes: Option<bool>,
-    include_plugins: Option<bool>,
-    description: Option<String>,
-}
-
-/// Create a new backup
-async fn create_backup_handler(
+/// Validate and save a plugin's settings
+async fn update_plugin_settings_handler(
     user: AuthUser,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    tenant: Option<axum::Extension<TenantId>>,
     State(state): State<AppState>,
-    Json(payload): Json<CreateBackupRequest>,
+    Json(payload): Json<UpdatePluginSettingsRequest>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
-    let backup_id = Uuid::now_v7();
-    let backup_type = payload.backup_type.unwrap_or_else(|| "full".to_string());
-    let now = chrono::Utc::now();
-    let name = format!("backup_{}_{}", backup_type, now.format("%Y%m%d_%H%M%S"));
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can change plugin settings",
+        ));
+    }
 
-    // Build includes JSONB
-    let includes = serde_json::json!({
-        "database": true,
-        "media": payload.include_media.unwrap_or(true),
-        "themes": payload.include_themes.unwrap_or(true),
-        "plugins": payload.include_plugins.unwrap_or(true)
-    });
+    let ctx = rustpress_core::context::AppContext::new(state.config().clone());
+    let tenant_id = tenant.as_deref().map(|TenantId(tenant)| tenant.as_str());
+    let saved = state
+        .plugins
+        .read()
+        .await
+        .update_settings(&id, tenant_id, payload.settings, payload.version, &ctx)
+        .await?;
 
-    // Create backup record (matching actual schema)
-    sqlx::query(
-        r#"
-        INSERT INTO backups (id, name, backup_type, status, includes, rustpress_version, started_at, created_at)
-        VALUES ($1, $2, $3, 'pending', $4, '1.0.0', $5, $5)
-        "#
-    )
-    .bind(backup_id)
-    .bind(&name)
-    .bind(&backup_type)
-    .bind(&includes)
-    .bind(now)
-    .execute(pool)
-    .await
-    .map_err(|e| rustpress_core::error::Error::database_with_source("Failed to create backup", e))?;
+    Ok(json(saved))
+}
 
-    // Queue backup job (in production this would be a background job)
+// =============================================================================
+// Theme Handlers
+// =============================================================================
 
-    Ok(created(serde_json::json!({
-        "id": backup_id,
-        "name": name,
-        "type": backup_type,
-        "status": "pending",
-        "message": "Backup job has been queued"
+/// List all installed themes
+async fn list_themes_handler(
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    // Get themes from database
+    let themes = state.theme_manager().list_themes().await?;
+    let active_theme = state.theme_manager().get_active_theme_id().await?;
+
+    Ok(json(serde_json::json!({
+        "themes": themes,
+        "total": themes.len(),
+        "active_theme": active_theme
     })))
 }
 
-/// Get a specific backup
-async fn get_backup_handler(
+/// Scan themes directory and register new themes
+async fn scan_themes_handler(
     user: AuthUser,
-    PathId(id): PathId,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
+    // Scan themes directory for new/updated themes
+    let scan_result = state.theme_manager().scan_themes().await?;
 
-    let backup: Option<(
-        Uuid,
-        String,
-        String,
-        Option<i64>,
-        String,
-        chrono::DateTime<chrono::Utc>,
-    )> = sqlx::query_as(
-        "SELECT id, name, backup_type, file_size, status, created_at FROM backups WHERE id = $1",
-    )
-    .bind(id)
-    .fetch_optional(pool)
-    .await
-    .map_err(|e| rustpress_core::error::Error::database_with_source("Failed to get backup", e))?;
+    Ok(json(serde_json::json!({
+        "scanned": scan_result.scanned,
+        "registered": scan_result.registered,
+        "updated": scan_result.updated,
+        "errors": scan_result.errors
+    })))
+}
 
-    match backup {
-        Some((id, name, backup_type, size, status, created_at)) => Ok(json(serde_json::json!({
-            "id": id,
-            "name": name,
-            "type": backup_type,
-            "size_bytes": size.unwrap_or(0),
-            "status": status,
-            "created_at": created_at
-        }))),
-        None => Err(rustpress_core::error::Error::not_found("Backup", id.to_string()).into()),
+/// Get the currently active theme
+async fn get_active_theme_handler(
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let theme = state.theme_manager().get_active_theme().await?;
+
+    match theme {
+        Some(t) => Ok(json(t)),
+        None => Err(rustpress_core::error::Error::not_found("Theme", "active").into()),
     }
 }
 
-/// Delete a backup
-async fn delete_backup_handler(
+/// Get render migration rollout, per-pipeline metrics and recent comparisons
+async fn get_render_migration_handler(
     user: AuthUser,
-    PathId(id): PathId,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
-
-    // Delete the backup record (actual schema doesn't have deleted_at)
-    sqlx::query("DELETE FROM backups WHERE id = $1")
-        .bind(id)
-        .execute(pool)
-        .await
-        .map_err(|e| {
-            rustpress_core::error::Error::database_with_source("Failed to delete backup", e)
-        })?;
-
-    Ok(no_content())
+    if !user.is_admin() {
+        return Err(HttpError::forbidden("Administrator access required"));
+    }
+    Ok(json(state.renderer().migration().report().await))
 }
 
-/// Download a backup
-async fn download_backup_handler(
+/// Update render migration rollout and comparison sampling
+async fn update_render_migration_handler(
     user: AuthUser,
-    PathId(id): PathId,
     State(state): State<AppState>,
+    Json(config): Json<crate::services::RenderMigrationConfig>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
+    if !user.is_admin() {
+        return Err(HttpError::forbidden("Administrator access required"));
+    }
+    let config = state
+        .renderer()
+        .migration()
+        .update(state.db().inner(), config)
+        .await?;
+    Ok(json(config))
+}
 
-    let backup: Option<(String, Option<String>)> = sqlx::query_as(
-        "SELECT name, file_path FROM backups WHERE id = $1 AND status = 'completed'",
-    )
-    .bind(id)
-    .fetch_optional(pool)
-    .await
-    .map_err(|e| rustpress_core::error::Error::database_with_source("Failed to get backup", e))?;
-
-    match backup {
-        Some((name, Some(file_path))) => {
-            // Read backup file
-            let contents = tokio::fs::read(&file_path).await.map_err(|e| {
-                rustpress_core::error::Error::internal(format!("Failed to read backup file: {}", e))
-            })?;
-
-            let disposition = format!("attachment; filename=\"{}.zip\"", name);
-
-            Ok(axum::response::Response::builder()
-                .status(axum::http::StatusCode::OK)
-                .header(header::CONTENT_TYPE, "application/zip")
-                .header(header::CONTENT_DISPOSITION, disposition)
-                .body(axum::body::Body::from(contents))
-                .unwrap()
-                .into_response())
-        }
-        Some((_, None)) => Err(rustpress_core::error::Error::internal(
-            "Backup file path not set".to_string(),
-        )
-        .into()),
-        None => Err(rustpress_core::error::Error::not_found("Backup", id.to_string()).into()),
-    }
-}
-
-/// Restore from backup
-async fn restore_backup_handler(
-    user: AuthUser,
-    PathId(id): PathId,
+/// Get a specific theme by its ID
+async fn get_theme_handler(
+    axum::extract::Path(theme_id): axum::extract::Path<String>,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let job_id = Uuid::now_v7();
+    let theme = state.theme_manager().get_theme(&theme_id).await?;
 
-    // In production, this would queue a restore job
-    Ok(json(serde_json::json!({
-        "job_id": job_id,
-        "status": "queued",
-        "message": "Restore job has been queued"
-    })))
+    match theme {
+        Some(t) => Ok(json(t)),
+        None => Err(crate::error::HttpError::not_found(format!(
+            "Theme '{}' not found",
+            theme_id
+        ))),
+    }
 }
 
-/// Get restore progress
-async fn restore_progress_handler(
+/// Delete/uninstall a theme
+async fn delete_theme_handler(
     user: AuthUser,
-    axum::extract::Path(job_id): axum::extract::Path<Uuid>,
+    axum::extract::Path(theme_id): axum::extract::Path<String>,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    // In production, this would check the job status
-    Ok(json(serde_json::json!({
-        "job_id": job_id,
-        "status": "completed",
-        "progress": 100,
-        "message": "Restore completed successfully"
-    })))
+    state.theme_manager().delete_theme(&theme_id).await?;
+    Ok(no_content())
 }
 
-/// Get backup storage usage
-async fn backup_storage_handler(
+/// Activate a theme
+async fn activate_theme_handler(
     user: AuthUser,
+    axum::extract::Path(theme_id): axum::extract::Path<String>,
+    tenant: Option<axum::Extension<TenantId>>,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
+    check_extension_allowed(&state, tenant.as_deref(), ExtensionKind::Theme, &theme_id).await?;
 
-    let total_size: (i64,) = sqlx::query_as("SELECT COALESCE(SUM(file_size), 0) FROM backups")
-        .fetch_one(pool)
-        .await
-        .unwrap_or((0,));
+    // Themes may conflict with active plugins, which the theme manager
+    // doesn't know about
+    let active_plugins = state.plugins.read().await.active_profiles();
+    state
+        .theme_manager()
+        .check_compatibility(&theme_id, &active_plugins)?
+        .into_result()?;
 
-    let backup_count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM backups")
-        .fetch_one(pool)
-        .await
-        .unwrap_or((0,));
+    let theme = state.theme_manager().activate_theme(&theme_id).await?;
 
     Ok(json(serde_json::json!({
-        "used_bytes": total_size.0,
-        "backup_count": backup_count.0,
-        "quota_bytes": 10_737_418_240i64, // 10GB default quota
-        "used_percentage": (total_size.0 as f64 / 10_737_418_240.0 * 100.0).round()
+        "success": true,
+        "message": format!("Theme '{}' activated successfully", theme.name),
+        "theme": theme
     })))
 }
 
-/// List backup schedules
-async fn list_backup_schedules_handler(
-    user: AuthUser,
+/// Get theme settings and customizer schema
+async fn get_theme_settings_handler(
+    axum::extract::Path(theme_id): axum::extract::Path<String>,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
-
-    // Using actual schema: frequency + cron_expression instead of schedule
-    let schedules: Vec<(Uuid, String, String, Option<String>, String, bool, Option<chrono::DateTime<chrono::Utc>>)> = sqlx::query_as(
-        "SELECT id, name, frequency, cron_expression, backup_type, enabled, next_run FROM backup_schedules"
-    )
-    .fetch_all(pool)
-    .await
-    .unwrap_or_default();
+    let settings = state.theme_manager().get_theme_settings(&theme_id).await?;
+    Ok(json(settings))
+}
 
-    let schedule_list: Vec<serde_json::Value> = schedules
-        .iter()
-        .map(
-            |(id, name, frequency, cron_expr, backup_type, enabled, next_run)| {
-                serde_json::json!({
-                    "id": id,
-                    "name": name,
-                    "frequency": frequency,
-                    "schedule": cron_expr.clone().unwrap_or_else(|| frequency.clone()),
-                    "type": backup_type,
-                    "enabled": enabled,
-                    "next_run_at": next_run
-                })
-            },
-        )
-        .collect();
+/// Update theme settings/customizations
+async fn update_theme_settings_handler(
+    user: AuthUser,
+    axum::extract::Path(theme_id): axum::extract::Path<String>,
+    State(state): State<AppState>,
+    Json(payload): Json<serde_json::Value>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let settings = state
+        .theme_manager()
+        .update_theme_settings(&theme_id, payload)
+        .await?;
 
-    Ok(json(serde_json::json!({ "schedules": schedule_list })))
+    Ok(json(serde_json::json!({
+        "success": true,
+        "settings": settings
+    })))
 }
 
-/// Create backup schedule request
-#[derive(Debug, Deserialize)]
-struct CreateBackupScheduleRequest {
-    name: String,
-    schedule: String, // cron expression or frequency
-    #[serde(rename = "type")]
-    backup_type: Option<String>,
-    retention_days: Option<i32>,
+/// Get menu assignments for a theme
+async fn get_theme_menus_handler(
+    axum::extract::Path(theme_id): axum::extract::Path<String>,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let menus = state
+        .theme_manager()
+        .get_menu_assignments(&theme_id)
+        .await?;
+    Ok(json(menus))
 }
 
-/// Create a backup schedule
-async fn create_backup_schedule_handler(
+/// Update menu assignments for a theme
+async fn update_theme_menus_handler(
     user: AuthUser,
+    axum::extract::Path(theme_id): axum::extract::Path<String>,
     State(state): State<AppState>,
-    Json(payload): Json<CreateBackupScheduleRequest>,
+    Json(payload): Json<serde_json::Value>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
-    let schedule_id = Uuid::now_v7();
-    let backup_type = payload.backup_type.unwrap_or_else(|| "full".to_string());
-    let now = chrono::Utc::now();
+    state
+        .theme_manager()
+        .update_menu_assignments(&theme_id, payload)
+        .await?;
 
-    // Build retention JSONB
-    let retention = serde_json::json!({
-        "retention_days": payload.retention_days.unwrap_or(30),
-        "max_backups": 10,
-        "min_backups": 1
-    });
+    Ok(json(serde_json::json!({
+        "success": true,
+        "message": "Menu assignments updated"
+    })))
+}
 
-    sqlx::query(
-        r#"
-        INSERT INTO backup_schedules (id, name, frequency, cron_expression, backup_type, retention, enabled, created_at, updated_at)
-        VALUES ($1, $2, 'custom', $3, $4, $5, true, $6, $6)
-        "#
-    )
-    .bind(schedule_id)
-    .bind(&payload.name)
-    .bind(&payload.schedule)
-    .bind(&backup_type)
-    .bind(&retention)
-    .bind(now)
-    .execute(pool)
-    .await
-    .map_err(|e| rustpress_core::error::Error::database_with_source("Failed to create schedule", e))?;
+/// Get the widget areas of a theme with their widgets
+async fn get_theme_widgets_handler(
+    axum::extract::Path(theme_id): axum::extract::Path<String>,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    Ok(json(state.widgets.areas(&theme_id).await?))
+}
 
-    Ok(created(serde_json::json!({
-        "id": schedule_id,
-        "name": payload.name,
-        "schedule": payload.schedule,
-        "type": backup_type,
-        "enabled": true
-    })))
+/// Widget placed at a position of a theme's widget area
+#[derive(Debug, Deserialize)]
+struct ThemeWidgetAssignment {
+    area_slug: String,
+    widget_id: Uuid,
+    position: i32,
 }
 
-/// Get backup schedule
-async fn get_backup_schedule_handler(
+/// Place widgets into the widget areas of a theme
+async fn update_theme_widgets_handler(
     user: AuthUser,
-    PathId(id): PathId,
+    axum::extract::Path(theme_id): axum::extract::Path<String>,
     State(state): State<AppState>,
+    Json(payload): Json<Vec<ThemeWidgetAssignment>>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    Ok(json(serde_json::json!({ "id": id })))
+    require_permission(&user, &state, "themes", "manage").await?;
+    let mut areas: std::collections::BTreeMap<String, Vec<(i32, Uuid)>> = Default::default();
+    for assignment in payload {
+        areas
+            .entry(assignment.area_slug)
+            .or_default()
+            .push((assignment.position, assignment.widget_id));
+    }
+    for (area, mut widgets) in areas {
+        widgets.sort();
+        let ids: Vec<Uuid> = widgets.into_iter().map(|(_, id)| id).collect();
+        state.widgets.place(&theme_id, &area, &ids).await?;
+    }
+    purge_widget_pages(&state).await;
+
+    Ok(json(serde_json::json!({
+        "success": true,
+        "message": "Widget assignments updated"
+    })))
 }
 
-/// Update backup schedule
-async fn update_backup_schedule_handler(
+/// Create a theme preview session
+async fn create_theme_preview_handler(
     user: AuthUser,
-    PathId(id): PathId,
+    axum::extract::Path(theme_id): axum::extract::Path<String>,
     State(state): State<AppState>,
     Json(payload): Json<serde_json::Value>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    Ok(json(serde_json::json!({ "id": id, "updated": true })))
+    let preview = state
+        .theme_manager()
+        .create_preview(user.id, &theme_id, payload)
+        .await?;
+
+    Ok(json(serde_json::json!({
+        "preview_token": preview.token,
+        "preview_url": format!("/?preview={}", preview.token),
+        "expires_at": preview.expires_at
+    })))
 }
 
-/// Delete backup schedule
-async fn delete_backup_schedule_handler(
+/// Upload and install a theme from ZIP file
+async fn upload_theme_handler(
     user: AuthUser,
-    PathId(id): PathId,
+    tenant: Option<axum::Extension<TenantId>>,
     State(state): State<AppState>,
+    mut multipart: axum::extract::Multipart,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
+    // Extract ZIP file from multipart
+    let mut zip_data: Option<Vec<u8>> = None;
+    let mut activate_after = false;
 
-    sqlx::query("DELETE FROM backup_schedules WHERE id = $1")
-        .bind(id)
-        .execute(pool)
-        .await
-        .map_err(|e| {
-            rustpress_core::error::Error::database_with_source("Failed to delete schedule", e)
-        })?;
-
-    Ok(no_content())
-}
-
-// =============================================================================
-// SEO Routes and Handlers
-// =============================================================================
-
-/// SEO management routes
-fn seo_routes() -> Router<AppState> {
-    Router::new()
-        .route(
-            "/settings",
-            get(get_seo_settings_handler).put(update_seo_settings_handler),
-        )
-        .route("/sitemap", get(get_sitemap_status_handler))
-        .route("/sitemap/generate", post(generate_sitemap_handler))
-        .route(
-            "/robots",
-            get(get_robots_txt_handler).put(update_robots_txt_handler),
-        )
-        .route("/analyze", post(analyze_seo_handler))
-        .route("/bulk-analyze", post(bulk_analyze_seo_handler))
-        .route("/dashboard", get(seo_dashboard_handler))
-        .route(
-            "/:content_type/:id",
-            get(get_content_seo_handler).put(update_content_seo_handler),
-        )
-}
-
-/// Get SEO settings
-async fn get_seo_settings_handler(
-    user: AuthUser,
-    State(state): State<AppState>,
-) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
+    while let Some(field) = multipart.next_field().await.map_err(|e| {
+        crate::error::HttpError::bad_request(format!("Failed to read multipart: {}", e))
+    })? {
+        let name = field.name().unwrap_or("").to_string();
 
-    // Get SEO-related settings
-    let settings: Vec<(String, serde_json::Value)> = sqlx::query_as(
-        "SELECT key, value FROM settings WHERE key LIKE 'seo_%' OR key LIKE 'site_%'",
-    )
-    .fetch_all(pool)
-    .await
-    .unwrap_or_default();
+        if name == "theme" || name == "file" {
+            zip_data = Some(
+                field
+                    .bytes()
+                    .await
+                    .map_err(|e| {
+                        crate::error::HttpError::bad_request(format!("Failed to read file: {}", e))
+                    })?
+                    .to_vec(),
+            );
+        } else if name == "activate" {
+            let value = field.text().await.unwrap_or_default();
+            activate_after = value == "true" || value == "1";
+        }
+    }
 
-    let mut seo_settings = serde_json::json!({
-        "site_title": "",
-        "site_description": "",
-        "title_separator": " | ",
-        "default_meta_robots": "index, follow",
-        "enable_open_graph": true,
-        "enable_twitter_cards": true,
-        "enable_schema_markup": true,
-        "google_analytics_id": "",
-        "google_search_console": "",
-        "bing_webmaster": ""
-    });
+    let zip_data = zip_data.ok_or_else(|| {
+        crate::error::HttpError::bad_request("No theme file provided".to_string())
+    })?;
 
-    for (key, value) in settings {
-        if let serde_json::Value::Object(ref mut map) = seo_settings {
-            map.insert(key, value);
-        }
+    // Refuse themes the tenant or site may not use before extracting
+    if let Some(theme_id) = state.theme_manager().validate_zip(&zip_data)?.theme_id {
+        check_extension_allowed(&state, tenant.as_deref(), ExtensionKind::Theme, &theme_id).await?;
     }
 
-    Ok(json(seo_settings))
+    // Install the theme
+    let result = state
+        .theme_manager()
+        .install_from_zip(&zip_data, activate_after)
+        .await?;
+
+    Ok(created(serde_json::json!({
+        "success": result.success,
+        "theme_id": result.theme_id,
+        "theme_name": result.theme_name,
+        "message": result.message,
+        "warnings": result.warnings
+    })))
 }
 
-/// Update SEO settings
-async fn update_seo_settings_handler(
+/// Validate a theme ZIP without installing
+async fn validate_theme_handler(
     user: AuthUser,
     State(state): State<AppState>,
-    Json(payload): Json<serde_json::Value>,
+    mut multipart: axum::extract::Multipart,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
+    let mut zip_data: Option<Vec<u8>> = None;
 
-    if let Some(obj) = payload.as_object() {
-        for (key, value) in obj {
-            sqlx::query(
-                r#"
-                INSERT INTO settings (id, key, value, group_name, updated_at)
-                VALUES (gen_random_uuid(), $1, $2, 'seo', NOW())
-                ON CONFLICT (key) DO UPDATE SET value = $2, updated_at = NOW()
-                "#,
-            )
-            .bind(key)
-            .bind(value)
-            .execute(pool)
-            .await
-            .ok();
+    while let Some(field) = multipart.next_field().await.map_err(|e| {
+        crate::error::HttpError::bad_request(format!("Failed to read multipart: {}", e))
+    })? {
+        let name = field.name().unwrap_or("").to_string();
+
+        if name == "theme" || name == "file" {
+            zip_data = Some(
+                field
+                    .bytes()
+                    .await
+                    .map_err(|e| {
+                        crate::error::HttpError::bad_request(format!("Failed to read file: {}", e))
+                    })?
+                    .to_vec(),
+            );
         }
     }
 
-    Ok(json(serde_json::json!({ "success": true })))
-}
-
-/// Get sitemap status
-async fn get_sitemap_status_handler(
-    State(state): State<AppState>,
-) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
-
-    let post_count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM posts WHERE post_type = 'post' AND status = 'published' AND deleted_at IS NULL")
-        .fetch_one(pool)
-        .await
-        .unwrap_or((0,));
+    let zip_data = zip_data.ok_or_else(|| {
+        crate::error::HttpError::bad_request("No theme file provided".to_string())
+    })?;
 
-    let page_count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM posts WHERE post_type = 'page' AND status = 'published' AND deleted_at IS NULL")
-        .fetch_one(pool)
-        .await
-        .unwrap_or((0,));
+    let result = state.theme_manager().validate_zip(&zip_data)?;
 
     Ok(json(serde_json::json!({
-        "url_count": post_count.0 + page_count.0 + 2, // +2 for home and blog
-        "last_generated": chrono::Utc::now(),
-        "sitemap_url": "/sitemap.xml"
+        "valid": result.valid,
+        "theme_id": result.theme_id,
+        "theme_name": result.theme_name,
+        "errors": result.errors,
+        "warnings": result.warnings
     })))
 }
 
-/// Generate sitemap
-async fn generate_sitemap_handler(
-    user: AuthUser,
+/// Get available default themes
+async fn get_available_themes_handler(
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    // In production, this would regenerate the sitemap
-    Ok(json(serde_json::json!({
-        "success": true,
-        "message": "Sitemap regenerated successfully"
-    })))
+    let available = state.theme_manager().get_available_themes();
+    Ok(json(serde_json::json!({ "themes": available })))
 }
 
-/// Get robots.txt content
-async fn get_robots_txt_handler(
+/// Update a theme from ZIP file
+async fn update_theme_handler(
+    user: AuthUser,
+    axum::extract::Path(theme_id): axum::extract::Path<String>,
     State(state): State<AppState>,
+    mut multipart: axum::extract::Multipart,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
+    let mut zip_data: Option<Vec<u8>> = None;
 
-    let robots: Option<(serde_json::Value,)> =
-        sqlx::query_as("SELECT value FROM settings WHERE key = 'robots_txt'")
-            .fetch_optional(pool)
-            .await
-            .ok()
-            .flatten();
+    while let Some(field) = multipart.next_field().await.map_err(|e| {
+        crate::error::HttpError::bad_request(format!("Failed to read multipart: {}", e))
+    })? {
+        let name = field.name().unwrap_or("").to_string();
+
+        if name == "theme" || name == "file" {
+            zip_data = Some(
+                field
+                    .bytes()
+                    .await
+                    .map_err(|e| {
+                        crate::error::HttpError::bad_request(format!("Failed to read file: {}", e))
+                    })?
+                    .to_vec(),
+            );
+        }
+    }
+
+    let zip_data = zip_data.ok_or_else(|| {
+        crate::error::HttpError::bad_request("No theme file provided".to_string())
+    })?;
 
-    let content = robots
-        .and_then(|(v,)| v.as_str().map(String::from))
-        .unwrap_or_else(|| "User-agent: *\nAllow: /\n\nSitemap: /sitemap.xml".to_string());
+    let result = state.theme_manager().update_from_zip(&zip_data).await?;
 
-    Ok(json(serde_json::json!({ "content": content })))
+    Ok(json(serde_json::json!({
+        "success": result.success,
+        "theme_id": result.theme_id,
+        "theme_name": result.theme_name,
+        "message": result.message,
+        "warnings": result.warnings
+    })))
 }
 
-/// Update robots.txt content
-async fn update_robots_txt_handler(
+/// Export a theme as ZIP file
+async fn export_theme_handler(
     user: AuthUser,
+    axum::extract::Path(theme_id): axum::extract::Path<String>,
     State(state): State<AppState>,
-    Json(payload): Json<serde_json::Value>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
+    let zip_data = state.theme_manager().export_theme(&theme_id).await?;
+    let disposition = format!("attachment; filename=\"{}.zip\"", theme_id);
 
-    let content = payload
-        .get("content")
-        .and_then(|v| v.as_str())
-        .unwrap_or("User-agent: *\nAllow: /");
+    Ok(axum::response::Response::builder()
+        .status(axum::http::StatusCode::OK)
+        .header(axum::http::header::CONTENT_TYPE, "application/zip")
+        .header(axum::http::header::CONTENT_DISPOSITION, disposition)
+        .body(axum::body::Body::from(zip_data))
+        .unwrap())
+}
 
-    sqlx::query(
-        r#"
-        INSERT INTO settings (id, key, value, group_name, updated_at)
-        VALUES (gen_random_uuid(), 'robots_txt', $1, 'seo', NOW())
-        ON CONFLICT (key) DO UPDATE SET value = $1, updated_at = NOW()
-        "#,
-    )
-    .bind(serde_json::json!(content))
-    .execute(pool)
-    .await
-    .map_err(|e| {
-        rustpress_core::error::Error::database_with_source("Failed to update robots.txt", e)
-    })?;
+// =============================================================================
+// Public Website Handlers (Theme Rendering)
+// =============================================================================
 
-    Ok(json(serde_json::json!({ "success": true })))
-}
+use crate::services::indexing::INDEXNOW_KEY_PATH;
+use crate::services::regions::inject_head_tags;
+use crate::services::web_vitals::{BEACON_PATH, MAX_BEACON_BYTES};
+use crate::services::{
+    encode_location, ArchiveQuery, DateArchive, FeedFormat, FeedScope, FeedValidators,
+    RenderedFeed, RobotsConfig,
+};
+use rustpress_core::config::current_environment;
 
-/// SEO analysis request
+/// Query params for public routes
 #[derive(Debug, Deserialize)]
-struct SeoAnalyzeRequest {
-    title: String,
-    content: String,
-    focus_keyword: Option<String>,
-    meta_description: Option<String>,
+struct PublicQueryParams {
+    page: Option<i32>,
+    preview: Option<String>,
+    /// Shortlink id (`/?p=123`)
+    p: Option<i64>,
 }
 
-/// Analyze content for SEO
-async fn analyze_seo_handler(
-    user: AuthUser,
-    Json(payload): Json<SeoAnalyzeRequest>,
-) -> HttpResult<impl axum::response::IntoResponse> {
-    let mut score = 0;
-    let mut suggestions = Vec::new();
+/// Convert rendered page to response
+fn rendered_response(
+    result: Result<crate::services::RenderedPage, rustpress_core::error::Error>,
+) -> Response {
+    match result {
+        Ok(page) => {
+            let mut response = Html(page.html).into_response();
+            let headers = response.headers_mut();
+            headers.insert(
+                header::CACHE_CONTROL,
+                page.cache_control
+                    .parse()
+                    .unwrap_or_else(|_| "no-cache".parse().unwrap()),
+            );
+            headers.insert(
+                header::CONTENT_TYPE,
+                page.content_type
+                    .parse()
+                    .unwrap_or_else(|_| "text/html".parse().unwrap()),
+            );
+            let extensions = response.extensions_mut();
+            extensions.insert(crate::services::ContentDescriptor {
+                keys: page.surrogate_keys.clone(),
+                flags: page.content_flags,
+            });
+            extensions.insert(crate::services::CacheHints {
+                surrogate_keys: page.surrogate_keys,
+                cache_override: page.cache_override,
+            });
+            response
+        }
+        Err(e) => {
+            let status = if e.to_string().contains("not found") {
+                axum::http::StatusCode::NOT_FOUND
+            } else {
+                axum::http::StatusCode::INTERNAL_SERVER_ERROR
+            };
+            (
+                status,
+                Html(format!(
+                    r#"<!DOCTYPE html>
+<html>
+<head><title>Error</title></head>
+<body>
+<h1>Error</h1>
+<p>{}</p>
+</body>
+</html>"#,
+                    e
+                )),
+            )
+                .into_response()
+        }
+    }
+}
 
-    // Title analysis
-    let title_len = payload.title.len();
-    if title_len >= 30 && title_len <= 60 {
-        score += 20;
-    } else if title_len < 30 {
-        suggestions.push("Title is too short. Aim for 30-60 characters.");
-    } else {
-        suggestions.push("Title is too long. Keep it under 60 characters.");
+/// Public home page handler
+async fn public_home_handler(
+    State(state): State<AppState>,
+    Query(params): Query<PublicQueryParams>,
+) -> Response {
+    if let Some(short_id) = params.p {
+        return shortlink_redirect(&state, short_id, params.preview.as_deref()).await;
     }
 
-    // Content length
-    let word_count = payload.content.split_whitespace().count();
-    if word_count >= 300 {
-        score += 20;
-    } else {
-        suggestions.push("Content is too short. Aim for at least 300 words.");
+    let mut result = state
+        .renderer()
+        .render_home(params.preview.as_deref())
+        .await;
+    if let Ok(page) = &mut result {
+        // Webmaster tools look for their verification tokens on the home page
+        let tags = state.indexing.verification_tags().await;
+        page.html = inject_head_tags(&page.html, &tags);
     }
+    rendered_response(result)
+}
 
-    // Meta description
-    if let Some(ref desc) = payload.meta_description {
-        let desc_len = desc.len();
-        if desc_len >= 120 && desc_len <= 160 {
-            score += 20;
-        } else if desc_len < 120 {
-            suggestions.push("Meta description is too short. Aim for 120-160 characters.");
-        } else {
-            suggestions.push("Meta description is too long. Keep it under 160 characters.");
+/// Redirect a `/?p=123` shortlink to the canonical post or page URL
+async fn shortlink_redirect(state: &AppState, short_id: i64, preview: Option<&str>) -> Response {
+    match state.content_filters.resolve_shortlink(short_id).await {
+        Ok(Some(path)) => {
+            let location = encode_location(&path);
+            axum::response::Redirect::permanent(&location).into_response()
+        }
+        Ok(None) => rendered_response(state.renderer().render_404(preview).await),
+        Err(e) => {
+            tracing::warn!(short_id, "Failed to resolve shortlink: {}", e);
+            rendered_response(Err(e))
         }
-    } else {
-        suggestions.push("Add a meta description for better SEO.");
     }
+}
 
-    // Focus keyword analysis
-    if let Some(ref keyword) = payload.focus_keyword {
-        if payload
-            .title
-            .to_lowercase()
-            .contains(&keyword.to_lowercase())
-        {
-            score += 15;
-        } else {
-            suggestions.push("Include your focus keyword in the title.");
-        }
+/// Public blog archive handler
+async fn public_blog_handler(
+    State(state): State<AppState>,
+    Query(params): Query<PublicQueryParams>,
+) -> Response {
+    // Blog page is same as home for now
+    let result = state
+        .renderer()
+        .render_home(params.preview.as_deref())
+        .await;
+    rendered_response(result)
+}
 
-        if payload
-            .content
-            .to_lowercase()
-            .contains(&keyword.to_lowercase())
-        {
-            score += 15;
-        } else {
-            suggestions.push("Include your focus keyword in the content.");
+/// Public single post handler
+async fn public_post_handler(
+    State(state): State<AppState>,
+    axum::extract::Path(slug): axum::extract::Path<String>,
+    Query(params): Query<PublicQueryParams>,
+) -> Response {
+    let result = state
+        .renderer()
+        .render_post(&slug, params.preview.as_deref())
+        .await;
+    rendered_response(result)
+}
+
+/// Public page handler
+async fn public_page_handler(
+    State(state): State<AppState>,
+    axum::extract::Path(slug): axum::extract::Path<String>,
+    Query(params): Query<PublicQueryParams>,
+) -> Response {
+    let result = state
+        .renderer()
+        .render_page(&slug, params.preview.as_deref())
+        .await;
+    rendered_response(result)
+}
+
+/// Query params for archive routes
+#[derive(Debug, Deserialize)]
+struct ArchiveQueryParams {
+    page: Option<i32>,
+    preview: Option<String>,
+    /// Intersect with these categories (`a+b`)
+    category: Option<String>,
+    /// Intersect with these tags (`a+b`)
+    tag: Option<String>,
+}
+
+impl ArchiveQueryParams {
+    /// Term filters on other taxonomies
+    fn filters(&self) -> Vec<(&str, &str)> {
+        let mut filters = Vec::new();
+        if let Some(ref category) = self.category {
+            filters.push(("category", category.as_str()));
         }
-    } else {
-        suggestions.push("Set a focus keyword for better optimization.");
+        if let Some(ref tag) = self.tag {
+            filters.push(("tag", tag.as_str()));
+        }
+        filters
     }
+}
 
-    // Readability (simple check)
-    let avg_sentence_len = payload.content.split('.').count();
-    if avg_sentence_len > 0 && word_count / avg_sentence_len < 25 {
-        score += 10;
-    } else {
-        suggestions.push("Consider using shorter sentences for better readability.");
+/// Render an archive page, redirecting to its canonical URL when the
+/// request used a different one (unsorted intersections, `?page=1`,
+/// trailing slashes). Invalid archives render the 404 page.
+async fn archive_response(
+    state: &AppState,
+    archive: rustpress_core::error::Result<ArchiveQuery>,
+    uri: &axum::http::Uri,
+    params: &ArchiveQueryParams,
+) -> Response {
+    let preview = params.preview.as_deref();
+    let archive = match archive {
+        Ok(archive) => archive,
+        Err(_) => return rendered_response(state.renderer().render_404(preview).await),
+    };
+
+    let page = params.page.unwrap_or(1);
+    let path = urlencoding::decode(uri.path())
+        .map(|path| path.into_owned())
+        .unwrap_or_else(|_| uri.path().to_string());
+
+    // Preview links are left alone so the preview token is kept
+    if preview.is_none() && (path != archive.base_path() || params.page == Some(1)) {
+        let location = encode_location(&archive.page_path(page));
+        return axum::response::Redirect::permanent(&location).into_response();
     }
 
-    Ok(json(serde_json::json!({
-        "score": score,
-        "max_score": 100,
-        "grade": if score >= 80 { "A" } else if score >= 60 { "B" } else if score >= 40 { "C" } else { "D" },
-        "suggestions": suggestions,
-        "word_count": word_count,
-        "reading_time_minutes": (word_count / 200).max(1)
-    })))
+    let result = state
+        .renderer()
+        .render_archive(&archive, page, preview)
+        .await;
+    rendered_response(result)
 }
 
-/// Bulk analyze request
+/// Path parameters of an archive feed; `format` is absent on the bare
+/// (RSS) feed URL
 #[derive(Debug, Deserialize)]
-struct BulkAnalyzeRequest {
-    ids: Vec<Uuid>,
-    #[serde(rename = "type")]
-    content_type: String,
+struct FeedPathParams {
+    slug: Option<String>,
+    year: Option<String>,
+    month: Option<String>,
+    day: Option<String>,
+    format: Option<String>,
+}
+
+impl FeedPathParams {
+    /// Date archive named by the path
+    fn date_archive(&self) -> rustpress_core::error::Result<ArchiveQuery> {
+        DateArchive::parse(
+            self.year.as_deref().unwrap_or_default(),
+            self.month.as_deref().unwrap_or_default(),
+            self.day.as_deref(),
+        )
+        .map(ArchiveQuery::Date)
+    }
 }
 
-/// Bulk analyze content for SEO
-async fn bulk_analyze_seo_handler(
-    user: AuthUser,
+/// Render a feed, answering conditional requests with `304 Not Modified`.
+/// Invalid archives and unknown formats render the 404 page.
+async fn feed_response(
+    state: &AppState,
+    scope: rustpress_core::error::Result<FeedScope>,
+    format: Option<&str>,
+    preview: Option<&str>,
+    headers: &axum::http::HeaderMap,
+) -> Response {
+    let format = match format {
+        Some(segment) => FeedFormat::from_segment(segment),
+        None => Some(FeedFormat::Rss),
+    };
+    let (Ok(scope), Some(format)) = (scope, format) else {
+        return rendered_response(state.renderer().render_404(preview).await);
+    };
+
+    match state.renderer().render_feed(&scope, format, preview).await {
+        Ok(feed) => conditional_feed_response(feed, headers),
+        Err(e) => rendered_response(Err(e)),
+    }
+}
+
+/// Answer a feed request, or `304 Not Modified` when the reader's copy is
+/// current
+fn conditional_feed_response(feed: RenderedFeed, headers: &axum::http::HeaderMap) -> Response {
+    let validators = FeedValidators::new(&feed.page.html, feed.last_modified);
+    let header_value = |name: header::HeaderName| headers.get(name).and_then(|v| v.to_str().ok());
+    let not_modified = validators.not_modified(
+        header_value(header::IF_NONE_MATCH),
+        header_value(header::IF_MODIFIED_SINCE),
+    );
+
+    let mut response = rendered_response(Ok(feed.page));
+    if not_modified {
+        *response.status_mut() = axum::http::StatusCode::NOT_MODIFIED;
+        *response.body_mut() = axum::body::Body::empty();
+    }
+    let response_headers = response.headers_mut();
+    if let Ok(etag) = axum::http::HeaderValue::from_str(&validators.etag) {
+        response_headers.insert(header::ETAG, etag);
+    }
+    if let Some(Ok(modified)) = validators
+        .last_modified_header()
+        .map(|value| axum::http::HeaderValue::from_str(&value))
+    {
+        response_headers.insert(header::LAST_MODIFIED, modified);
+    }
+    response
+}
+
+/// Public category archive handler
+async fn public_category_handler(
     State(state): State<AppState>,
-    Json(payload): Json<BulkAnalyzeRequest>,
-) -> HttpResult<impl axum::response::IntoResponse> {
-    // In production, this would queue analysis jobs
-    Ok(json(serde_json::json!({
-        "queued": payload.ids.len(),
-        "message": "Analysis jobs queued"
-    })))
+    axum::extract::Path(slug): axum::extract::Path<String>,
+    uri: axum::http::Uri,
+    Query(params): Query<ArchiveQueryParams>,
+) -> Response {
+    let archive = ArchiveQuery::terms("category", &slug, &params.filters());
+    archive_response(&state, archive, &uri, &params).await
 }
 
-/// SEO dashboard statistics
-async fn seo_dashboard_handler(
-    user: AuthUser,
+/// Public category feed handler
+async fn public_category_feed_handler(
     State(state): State<AppState>,
-) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
+    axum::extract::Path(path): axum::extract::Path<FeedPathParams>,
+    Query(params): Query<ArchiveQueryParams>,
+    headers: axum::http::HeaderMap,
+) -> Response {
+    let slug = path.slug.as_deref().unwrap_or_default();
+    let scope = ArchiveQuery::terms("category", slug, &params.filters()).map(FeedScope::Archive);
+    let preview = params.preview.as_deref();
+    feed_response(&state, scope, path.format.as_deref(), preview, &headers).await
+}
 
-    let post_count: (i64,) = sqlx::query_as(
-        "SELECT COUNT(*) FROM posts WHERE status = 'published' AND deleted_at IS NULL",
-    )
-    .fetch_one(pool)
-    .await
-    .unwrap_or((0,));
+/// Public tag archive handler
+async fn public_tag_handler(
+    State(state): State<AppState>,
+    axum::extract::Path(slug): axum::extract::Path<String>,
+    uri: axum::http::Uri,
+    Query(params): Query<ArchiveQueryParams>,
+) -> Response {
+    let archive = ArchiveQuery::terms("tag", &slug, &params.filters());
+    archive_response(&state, archive, &uri, &params).await
+}
 
-    Ok(json(serde_json::json!({
-        "total_pages_indexed": post_count.0,
-        "average_seo_score": 72,
-        "pages_without_meta": 5,
-        "pages_with_issues": 12,
-        "sitemap_urls": post_count.0 + 2
-    })))
+/// Public tag feed handler
+async fn public_tag_feed_handler(
+    State(state): State<AppState>,
+    axum::extract::Path(path): axum::extract::Path<FeedPathParams>,
+    Query(params): Query<ArchiveQueryParams>,
+    headers: axum::http::HeaderMap,
+) -> Response {
+    let slug = path.slug.as_deref().unwrap_or_default();
+    let scope = ArchiveQuery::terms("tag", slug, &params.filters()).map(FeedScope::Archive);
+    let preview = params.preview.as_deref();
+    feed_response(&state, scope, path.format.as_deref(), preview, &headers).await
 }
 
-/// Get SEO metadata for specific content
-async fn get_content_seo_handler(
-    axum::extract::Path((content_type, id)): axum::extract::Path<(String, Uuid)>,
+/// Public author archive handler
+async fn public_author_handler(
     State(state): State<AppState>,
-) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
+    axum::extract::Path(slug): axum::extract::Path<String>,
+    uri: axum::http::Uri,
+    Query(params): Query<ArchiveQueryParams>,
+) -> Response {
+    let archive = Ok(ArchiveQuery::Author(slug));
+    archive_response(&state, archive, &uri, &params).await
+}
 
-    // Get SEO metadata from post_meta or page_meta tables
-    let seo: Option<(Option<String>, Option<String>, Option<String>)> = match content_type.as_str() {
-        "post" | "posts" => {
-            sqlx::query_as(
-                r#"
-                SELECT
-                    (SELECT value FROM post_meta WHERE post_id = $1 AND key = 'seo_title') as seo_title,
-                    (SELECT value FROM post_meta WHERE post_id = $1 AND key = 'seo_description') as seo_description,
-                    (SELECT value FROM post_meta WHERE post_id = $1 AND key = 'focus_keyword') as focus_keyword
-                "#
-            )
-            .bind(id)
-            .fetch_optional(pool)
-            .await
-            .ok()
-            .flatten()
-        }
-        _ => None
-    };
-
-    let (seo_title, seo_description, focus_keyword) = seo.unwrap_or((None, None, None));
+/// Public author feed handler
+async fn public_author_feed_handler(
+    State(state): State<AppState>,
+    axum::extract::Path(path): axum::extract::Path<FeedPathParams>,
+    Query(params): Query<ArchiveQueryParams>,
+    headers: axum::http::HeaderMap,
+) -> Response {
+    let slug = path.slug.clone().unwrap_or_default();
+    let scope = Ok(FeedScope::Archive(ArchiveQuery::Author(slug)));
+    let preview = params.preview.as_deref();
+    feed_response(&state, scope, path.format.as_deref(), preview, &headers).await
+}
+
+/// Archive named by a two-segment path: a month (`/2024/05`) or a term of
+/// a custom taxonomy (`/genre/jazz`)
+fn two_segment_archive(
+    first: &str,
+    second: &str,
+    params: &ArchiveQueryParams,
+) -> rustpress_core::error::Result<ArchiveQuery> {
+    if first.starts_with(|c: char| c.is_ascii_digit()) {
+        DateArchive::parse(first, second, None).map(ArchiveQuery::Date)
+    } else {
+        ArchiveQuery::terms(first, second, &params.filters())
+    }
+}
 
-    Ok(json(serde_json::json!({
-        "id": id,
-        "type": content_type,
-        "seo_title": seo_title,
-        "meta_description": seo_description,
-        "focus_keyword": focus_keyword,
-        "og_title": seo_title,
-        "og_description": seo_description,
-        "twitter_title": seo_title,
-        "twitter_description": seo_description
-    })))
+/// Public month archive handler (`/2024/05`); also serves custom taxonomy
+/// term archives (`/genre/jazz`)
+async fn public_month_archive_handler(
+    State(state): State<AppState>,
+    axum::extract::Path((year, month)): axum::extract::Path<(String, String)>,
+    uri: axum::http::Uri,
+    Query(params): Query<ArchiveQueryParams>,
+) -> Response {
+    let archive = two_segment_archive(&year, &month, &params);
+    archive_response(&state, archive, &uri, &params).await
 }
 
-/// Update SEO metadata for specific content
-async fn update_content_seo_handler(
-    user: AuthUser,
-    axum::extract::Path((content_type, id)): axum::extract::Path<(String, Uuid)>,
+/// Public month archive feed handler; also serves custom taxonomy term
+/// archive feeds
+async fn public_month_feed_handler(
     State(state): State<AppState>,
-    Json(payload): Json<serde_json::Value>,
-) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
+    axum::extract::Path(path): axum::extract::Path<FeedPathParams>,
+    Query(params): Query<ArchiveQueryParams>,
+    headers: axum::http::HeaderMap,
+) -> Response {
+    let scope = two_segment_archive(
+        path.year.as_deref().unwrap_or_default(),
+        path.month.as_deref().unwrap_or_default(),
+        &params,
+    )
+    .map(FeedScope::Archive);
+    let preview = params.preview.as_deref();
+    feed_response(&state, scope, path.format.as_deref(), preview, &headers).await
+}
 
-    // Update SEO metadata
-    if let Some(obj) = payload.as_object() {
-        for (key, value) in obj {
-            let meta_key = format!("seo_{}", key);
-            let value_str = value.as_str().unwrap_or("");
+/// Public day archive handler (`/2024/05/14`)
+async fn public_day_archive_handler(
+    State(state): State<AppState>,
+    axum::extract::Path((year, month, day)): axum::extract::Path<(String, String, String)>,
+    uri: axum::http::Uri,
+    Query(params): Query<ArchiveQueryParams>,
+) -> Response {
+    let archive = DateArchive::parse(&year, &month, Some(&day)).map(ArchiveQuery::Date);
+    archive_response(&state, archive, &uri, &params).await
+}
 
-            sqlx::query(
-                r#"
-                INSERT INTO post_meta (id, post_id, key, value, created_at, updated_at)
-                VALUES (gen_random_uuid(), $1, $2, $3, NOW(), NOW())
-                ON CONFLICT (post_id, key) DO UPDATE SET value = $3, updated_at = NOW()
-                "#,
-            )
-            .bind(id)
-            .bind(&meta_key)
-            .bind(value_str)
-            .execute(pool)
-            .await
-            .ok();
-        }
-    }
+/// Public day archive feed handler
+async fn public_day_feed_handler(
+    State(state): State<AppState>,
+    axum::extract::Path(path): axum::extract::Path<FeedPathParams>,
+    Query(params): Query<ArchiveQueryParams>,
+    headers: axum::http::HeaderMap,
+) -> Response {
+    let scope = path.date_archive().map(FeedScope::Archive);
+    let preview = params.preview.as_deref();
+    feed_response(&state, scope, path.format.as_deref(), preview, &headers).await
+}
 
-    Ok(json(serde_json::json!({ "success": true })))
+/// Search query params
+#[derive(Debug, Deserialize)]
+struct SearchQueryParams {
+    q: Option<String>,
+    page: Option<i32>,
+    preview: Option<String>,
 }
 
-// =============================================================================
-// Cache Routes and Handlers
-// =============================================================================
+/// Public search results handler
+async fn public_search_handler(
+    State(state): State<AppState>,
+    Query(params): Query<SearchQueryParams>,
+) -> Response {
+    let query = params.q.unwrap_or_default();
+    let page = params.page.unwrap_or(1);
 
-/// Cache management routes
-fn cache_routes() -> Router<AppState> {
-    Router::new()
-        .route("/stats", get(cache_stats_handler))
-        .route("/clear", post(clear_all_cache_handler))
-        .route("/clear/:cache_type", post(clear_cache_by_type_handler))
-        .route("/clear/tag", post(clear_cache_by_tag_handler))
-        .route(
-            "/clear/:content_type/:id",
-            post(clear_content_cache_handler),
-        )
-        .route("/entries", get(list_cache_entries_handler))
-        .route(
-            "/config",
-            get(get_cache_config_handler).put(update_cache_config_handler),
+    if query.is_empty() {
+        return Html(
+            r#"<!DOCTYPE html>
+<html>
+<head><title>Search</title></head>
+<body>
+<h1>Search</h1>
+<form action="/search" method="get">
+<input type="search" name="q" placeholder="Search...">
+<button type="submit">Search</button>
+</form>
+</body>
+</html>"#,
         )
-        .route("/warm", post(warm_cache_handler))
-        .route("/health", get(cache_health_handler))
+        .into_response();
+    }
+
+    let result = state
+        .renderer()
+        .render_search(&query, page, params.preview.as_deref())
+        .await;
+    rendered_response(result)
 }
 
-/// Get cache statistics
-async fn cache_stats_handler(
-    user: AuthUser,
+/// Public site feed handler
+async fn public_feed_handler(
     State(state): State<AppState>,
-) -> HttpResult<impl axum::response::IntoResponse> {
-    let cache = state.cache();
+    format: Option<axum::extract::Path<String>>,
+    Query(params): Query<ArchiveQueryParams>,
+    headers: axum::http::HeaderMap,
+) -> Response {
+    let format = format.map(|axum::extract::Path(format)| format);
+    let preview = params.preview.as_deref();
+    feed_response(
+        &state,
+        Ok(FeedScope::Site),
+        format.as_deref(),
+        preview,
+        &headers,
+    )
+    .await
+}
 
-    // Get cache stats
-    let stats = cache.stats().await;
+/// Public sitemap handler
+async fn public_sitemap_handler(State(state): State<AppState>) -> impl IntoResponse {
+    // Generate XML sitemap
+    let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+    <url>
+        <loc>http://localhost:3000/</loc>
+        <lastmod>2025-12-15</lastmod>
+        <changefreq>daily</changefreq>
+        <priority>1.0</priority>
+    </url>
+    <url>
+        <loc>http://localhost:3000/blog</loc>
+        <lastmod>2025-12-15</lastmod>
+        <changefreq>daily</changefreq>
+        <priority>0.8</priority>
+    </url>
+</urlset>"#;
 
-    Ok(json(serde_json::json!({
-        "hit_count": stats.hits,
-        "miss_count": stats.misses,
-        "hit_rate": if stats.hits + stats.misses > 0 {
-            (stats.hits as f64 / (stats.hits + stats.misses) as f64 * 100.0).round()
-        } else { 0.0 },
-        "total_entries": stats.entries,
-        "memory_usage_bytes": stats.memory_bytes,
-        "evictions": stats.evictions,
-        "by_type": {
-            "page": { "entries": stats.entries / 3, "size_bytes": stats.memory_bytes / 3 },
-            "query": { "entries": stats.entries / 3, "size_bytes": stats.memory_bytes / 3 },
-            "object": { "entries": stats.entries / 3, "size_bytes": stats.memory_bytes / 3 }
-        }
-    })))
+    (
+        [(header::CONTENT_TYPE, "application/xml; charset=utf-8")],
+        xml,
+    )
 }
 
-/// Clear all cache
-async fn clear_all_cache_handler(
-    user: AuthUser,
-    State(state): State<AppState>,
-) -> HttpResult<impl axum::response::IntoResponse> {
-    let cache = state.cache();
-    cache.clear().await.map_err(|e| {
-        rustpress_core::error::Error::internal(format!("Cache clear failed: {}", e))
-    })?;
+/// Public robots.txt handler
+async fn public_robots_handler(State(state): State<AppState>) -> impl IntoResponse {
+    let renderer = state.renderer();
+    let config = renderer.robots_config().await;
+    let robots = config.render(&current_environment(), &renderer.site_url().await);
 
-    Ok(json(serde_json::json!({
-        "success": true,
-        "message": "All cache cleared"
-    })))
+    (
+        [(header::CONTENT_TYPE, "text/plain; charset=utf-8")],
+        robots,
+    )
 }
 
-/// Clear cache by type
-async fn clear_cache_by_type_handler(
-    user: AuthUser,
-    axum::extract::Path(cache_type): axum::extract::Path<String>,
+/// IndexNow key file; search engines fetch it to check submissions
+async fn indexnow_key_handler(State(state): State<AppState>) -> Response {
+    match state.indexing.indexnow_key().await {
+        Some(key) => ([(header::CONTENT_TYPE, "text/plain; charset=utf-8")], key).into_response(),
+        None => (axum::http::StatusCode::NOT_FOUND, "Not found").into_response(),
+    }
+}
+
+/// Core Web Vitals measured by a visitor's browser. `sendBeacon` posts the
+/// JSON as text/plain, so the body is parsed whatever its content type
+async fn web_vitals_beacon_handler(
     State(state): State<AppState>,
-) -> HttpResult<impl axum::response::IntoResponse> {
-    let cache = state.cache();
-    let _ = cache.clear_by_prefix(&cache_type).await;
+    traffic: Option<axum::Extension<crate::security::TrafficClass>>,
+    headers: axum::http::HeaderMap,
+    body: axum::body::Bytes,
+) -> Response {
+    use axum::http::StatusCode;
 
-    Ok(json(serde_json::json!({
-        "success": true,
-        "message": format!("Cache type '{}' cleared", cache_type)
-    })))
+    if body.len() > MAX_BEACON_BYTES {
+        return StatusCode::PAYLOAD_TOO_LARGE.into_response();
+    }
+    // Only visitors' page views count, as in first-party analytics
+    if traffic.is_some_and(|axum::Extension(class)| !class.counts_in_analytics()) {
+        return StatusCode::NO_CONTENT.into_response();
+    }
+    let Ok(beacon) = serde_json::from_slice::<crate::services::VitalsBeacon>(&body) else {
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+    let user_agent = headers
+        .get(header::USER_AGENT)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    let site_url = state.renderer().site_url().await;
+    match state
+        .web_vitals
+        .record(&beacon, user_agent, &site_url)
+        .await
+    {
+        Ok(_) => StatusCode::NO_CONTENT.into_response(),
+        Err(rustpress_core::error::Error::InvalidInput { .. }) => {
+            StatusCode::BAD_REQUEST.into_response()
+        }
+        Err(e) => {
+            tracing::warn!("Failed to record Core Web Vitals: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
 }
 
-/// Clear cache by tag request
-#[derive(Debug, Deserialize)]
-struct ClearByTagRequest {
-    tag: String,
-}
+/// Requests to the inbound email webhooks carry a whole message, base64
+/// encoded by SES
+const INBOUND_EMAIL_BODY_LIMIT: usize = crate::services::inbound_email::MAX_MESSAGE_SIZE * 2;
 
-/// Clear cache by tag
-async fn clear_cache_by_tag_handler(
-    user: AuthUser,
+/// A message forwarded by a Mailgun route: `forward()` to a URL ending in
+/// `mime` posts the raw message as `body-mime`, signed with the mailbox's
+/// webhook signing key
+async fn mailgun_inbound_email_handler(
     State(state): State<AppState>,
-    Json(payload): Json<ClearByTagRequest>,
+    axum::extract::Path(token): axum::extract::Path<String>,
+    mut multipart: Multipart,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let cache = state.cache();
-    let _ = cache.clear_by_tag(&payload.tag).await;
+    let mailbox = state
+        .inbound_email
+        .by_token(MailboxKind::Mailgun, &token)
+        .await?;
 
-    Ok(json(serde_json::json!({
-        "success": true,
-        "message": format!("Cache with tag '{}' cleared", payload.tag)
-    })))
+    let mut raw = None;
+    let mut fields = std::collections::HashMap::new();
+    while let Some(field) = multipart.next_field().await.map_err(|e| {
+        rustpress_core::error::Error::validation(format!("Failed to read multipart: {}", e))
+    })? {
+        let name = field.name().unwrap_or("").to_string();
+        match name.as_str() {
+            "body-mime" => {
+                raw = Some(field.bytes().await.map_err(|e| {
+                    rustpress_core::error::Error::validation(format!(
+                        "Failed to read message: {}",
+                        e
+                    ))
+                })?);
+            }
+            "timestamp" | "token" | "signature" => {
+                fields.insert(name, field.text().await.unwrap_or_default());
+            }
+            _ => {}
+        }
+    }
+    let field = |name: &str| fields.get(name).map(String::as_str).unwrap_or_default();
+    state.inbound_email.verify_mailgun(
+        &mailbox,
+        field("timestamp"),
+        field("token"),
+        field("signature"),
+    )?;
+    let raw = raw.ok_or_else(|| HttpError::bad_request("body-mime is missing"))?;
+
+    Ok(json(state.inbound_email.receive(&mailbox, &raw).await?))
 }
 
-/// Clear cache for specific content
-async fn clear_content_cache_handler(
-    user: AuthUser,
-    axum::extract::Path((content_type, id)): axum::extract::Path<(String, Uuid)>,
+/// An SNS message for an SES mailbox. SNS posts JSON as text/plain, so the
+/// body is parsed whatever its content type
+async fn ses_inbound_email_handler(
     State(state): State<AppState>,
+    axum::extract::Path(token): axum::extract::Path<String>,
+    body: axum::body::Bytes,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let cache = state.cache();
-    let key = format!("{}:{}", content_type, id);
-    let _ = cache.delete(&key).await;
+    let mailbox = state
+        .inbound_email
+        .by_token(MailboxKind::Ses, &token)
+        .await?;
+    let notification: serde_json::Value =
+        serde_json::from_slice(&body).map_err(|_| HttpError::bad_request("Not an SNS message"))?;
 
-    Ok(json(serde_json::json!({
-        "success": true,
-        "message": format!("Cache for {}:{} cleared", content_type, id)
-    })))
+    match state
+        .inbound_email
+        .receive_ses(&mailbox, &notification)
+        .await?
+    {
+        Some(message) => Ok(json(message).into_response()),
+        None => Ok(no_content().into_response()),
+    }
 }
 
-/// Cache entries query
+/// Theme static asset handler
+async fn theme_asset_handler(
+    State(state): State<AppState>,
+    axum::extract::Path((theme_id, path)): axum::extract::Path<(String, String)>,
+) -> Response {
+    use tokio::fs;
+
+    // Build the file path - get themes_dir from theme manager
+    let themes_dir = state.theme_manager().themes_dir().to_path_buf();
+    let file_path = themes_dir.join(&theme_id).join(&path);
+
+    // Security: ensure path doesn't escape themes directory
+    let canonical = match file_path.canonicalize() {
+        Ok(p) => p,
+        Err(_) => {
+            return (axum::http::StatusCode::NOT_FOUND, "File not found").into_response();
+        }
+    };
+
+    let themes_canonical = themes_dir.canonicalize().unwrap_or(themes_dir);
+    if !canonical.starts_with(&themes_canonical) {
+        return (axum::http::StatusCode::FORBIDDEN, "Access denied").into_response();
+    }
+
+    // Read file
+    match fs::read(&canonical).await {
+        Ok(contents) => {
+            // Determine content type
+            let content_type = match file_path.extension().and_then(|e| e.to_str()) {
+                Some("css") => "text/css",
+                Some("js") => "application/javascript",
+                Some("png") => "image/png",
+                Some("jpg") | Some("jpeg") => "image/jpeg",
+                Some("gif") => "image/gif",
+                Some("svg") => "image/svg+xml",
+                Some("woff") => "font/woff",
+                Some("woff2") => "font/woff2",
+                Some("ttf") => "font/ttf",
+                Some("eot") => "application/vnd.ms-fontobject",
+                Some("ico") => "image/x-icon",
+                Some("webp") => "image/webp",
+                Some("json") => "application/json",
+                Some("xml") => "application/xml",
+                _ => "application/octet-stream",
+            };
+
+            (
+                [
+                    (header::CONTENT_TYPE, content_type),
+                    (header::CACHE_CONTROL, "public, max-age=31536000"),
+                ],
+                contents,
+            )
+                .into_response()
+        }
+        Err(_) => (axum::http::StatusCode::NOT_FOUND, "File not found").into_response(),
+    }
+}
+
+// =============================================================================
+// Search Routes and Handlers
+// =============================================================================
+
+/// Search routes for full-text search
+fn search_routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(search_handler))
+        .route("/suggest", get(search_suggest_handler))
+        .route("/reindex", post(search_reindex_handler))
+        .route("/stats", get(search_stats_handler))
+        .route("/admin", get(admin_search_handler))
+}
+
+/// Search query parameters
 #[derive(Debug, Deserialize)]
-struct CacheEntriesQuery {
-    prefix: Option<String>,
+struct ApiSearchQuery {
+    q: String,
+    #[serde(rename = "type")]
+    content_type: Option<String>,
     page: Option<u32>,
     per_page: Option<u32>,
 }
 
-/// List cache entries
-async fn list_cache_entries_handler(
-    user: AuthUser,
-    Query(query): Query<CacheEntriesQuery>,
+/// Full-text search handler
+async fn search_handler(
+    Query(query): Query<ApiSearchQuery>,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let cache = state.cache();
-    let entries = cache.list_keys(query.prefix.as_deref()).await;
+    let kinds = match query.content_type.as_deref() {
+        Some(types) => DocumentKind::parse_list(types)?,
+        None => Vec::new(),
+    };
+    let search = SearchQuery::new(query.q)
+        .kinds(kinds)
+        .page(query.page.unwrap_or(1), query.per_page.unwrap_or(20));
 
-    let page = query.page.unwrap_or(1) as usize;
-    let per_page = query.per_page.unwrap_or(50) as usize;
-    let offset = (page - 1) * per_page;
+    Ok(json(state.search.search(search).await?))
+}
 
-    let paginated: Vec<serde_json::Value> = entries
-        .iter()
-        .skip(offset)
-        .take(per_page)
-        .map(|key| serde_json::json!({ "key": key }))
-        .collect();
+/// Search suggestions handler
+async fn search_suggest_handler(
+    Query(query): Query<ApiSearchQuery>,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let suggestions = state
+        .search
+        .suggest(&query.q, query.per_page.unwrap_or(10))
+        .await?;
 
-    Ok(json(serde_json::json!({
-        "entries": paginated,
-        "total": entries.len(),
-        "page": page,
-        "per_page": per_page
-    })))
+    Ok(json(serde_json::json!({ "suggestions": suggestions })))
 }
 
-/// Get cache configuration
-async fn get_cache_config_handler(
+/// Rebuild the search index in the background
+async fn search_reindex_handler(
     user: AuthUser,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can rebuild the search index",
+        ));
+    }
+
+    let search = state.search.clone();
+    tokio::spawn(async move {
+        if let Err(e) = search.reindex().await {
+            tracing::error!("Search reindex failed: {}", e);
+        }
+    });
+
     Ok(json(serde_json::json!({
-        "enabled": true,
-        "default_ttl_seconds": 3600,
-        "max_entries": 10000,
-        "max_memory_bytes": 104857600, // 100MB
-        "page_cache_enabled": true,
-        "query_cache_enabled": true,
-        "object_cache_enabled": true,
-        "api_cache_enabled": true
+        "status": "queued",
+        "message": "Search reindex has been started",
+        "backend": state.search.backend().name()
     })))
 }
 
-/// Update cache configuration
-async fn update_cache_config_handler(
-    user: AuthUser,
+/// Get search statistics
+async fn search_stats_handler(
+    _user: AuthUser,
     State(state): State<AppState>,
-    Json(payload): Json<serde_json::Value>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    // In production, this would update cache settings
-    Ok(json(serde_json::json!({
-        "success": true,
-        "message": "Cache configuration updated"
-    })))
+    Ok(json(state.search.stats().await?))
 }
 
-/// Warm up cache request
+use crate::services::{AdminSearchQuery, DocumentKind, SearchQuery, SearchUser, TrashFilter};
+
+/// Command palette query parameters
 #[derive(Debug, Deserialize)]
-struct WarmCacheRequest {
-    types: Option<Vec<String>>,
-    urls: Option<Vec<String>>,
+struct AdminSearchParams {
+    q: String,
+    /// Comma-separated kinds to search; all when absent
+    types: Option<String>,
+    #[serde(default)]
+    trash: TrashFilter,
+    limit: Option<usize>,
 }
 
-/// Warm up cache
-async fn warm_cache_handler(
+/// Command palette search across admin screens and content
+async fn admin_search_handler(
     user: AuthUser,
-    State(state): State<AppState>,
-    Json(payload): Json<WarmCacheRequest>,
-) -> HttpResult<impl axum::response::IntoResponse> {
-    // In production, this would queue cache warming jobs
-    let types = payload
-        .types
-        .unwrap_or_else(|| vec!["posts".to_string(), "pages".to_string()]);
-
-    Ok(json(serde_json::json!({
-        "success": true,
-        "message": "Cache warm-up job queued",
-        "types": types
-    })))
-}
-
-/// Get cache health
-async fn cache_health_handler(
+    Query(params): Query<AdminSearchParams>,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let cache = state.cache();
-    let stats = cache.stats().await;
-
-    let status = if stats.hits + stats.misses > 0 {
-        let hit_rate = stats.hits as f64 / (stats.hits + stats.misses) as f64;
-        if hit_rate >= 0.8 {
-            "healthy"
-        } else if hit_rate >= 0.5 {
-            "degraded"
-        } else {
-            "poor"
-        }
-    } else {
-        "unknown"
-    };
-
-    Ok(json(serde_json::json!({
-        "status": status,
-        "details": {
-            "hit_rate": if stats.hits + stats.misses > 0 {
-                (stats.hits as f64 / (stats.hits + stats.misses) as f64 * 100.0).round()
-            } else { 0.0 },
-            "memory_pressure": "low",
-            "eviction_rate": "normal"
-        }
-    })))
+    let query = AdminSearchQuery::new(
+        &params.q,
+        params.types.as_deref(),
+        params.trash,
+        params.limit,
+    )?;
+    let active_plugins: std::collections::HashSet<String> = state
+        .plugins
+        .read()
+        .await
+        .list_active()
+        .into_iter()
+        .map(|plugin| plugin.id)
+        .collect();
+    let results = state
+        .admin_search
+        .search(
+            &SearchUser::new(user.id, user.roles.clone()),
+            &query,
+            &active_plugins,
+        )
+        .await?;
+    Ok(json(results))
 }
 
 // =============================================================================
-// CDN Routes and Handlers
+// Backup Routes and Handlers
 // =============================================================================
 
-/// CDN management routes
-fn cdn_routes() -> Router<AppState> {
+/// Backup management routes
+fn backup_routes() -> Router<AppState> {
     Router::new()
-        .route("/status", get(cdn_status_handler))
+        .route("/", get(list_backups_handler).post(create_backup_handler))
+        .route("/storage", get(backup_storage_handler))
         .route(
-            "/config",
-            get(get_cdn_config_handler).put(update_cdn_config_handler),
+            "/schedules",
+            get(list_backup_schedules_handler).post(create_backup_schedule_handler),
         )
-        .route("/purge/all", post(cdn_purge_all_handler))
-        .route("/purge/urls", post(cdn_purge_urls_handler))
-        .route("/purge/tags", post(cdn_purge_tags_handler))
-        .route("/stats", get(cdn_stats_handler))
-        .route("/health", get(cdn_health_handler))
+        .route(
+            "/schedules/:id",
+            get(get_backup_schedule_handler)
+                .put(update_backup_schedule_handler)
+                .delete(delete_backup_schedule_handler),
+        )
+        .route(
+            "/:id",
+            get(get_backup_handler).delete(delete_backup_handler),
+        )
+        .route("/:id/download", get(download_backup_handler))
+        .route("/:id/restore", post(restore_backup_handler))
+        .route("/restore/:job_id", get(restore_progress_handler))
+        // Backups and restores run for minutes
+        .route_layer(axum::middleware::from_fn(
+            crate::transaction::without_request_transaction,
+        ))
 }
 
-/// CDN purge URLs request
+/// Backup list query
 #[derive(Debug, Deserialize)]
-struct CdnPurgeUrlsRequest {
-    urls: Vec<String>,
+struct BackupListQuery {
+    page: Option<u32>,
+    per_page: Option<u32>,
+    #[serde(rename = "type")]
+    backup_type: Option<String>,
 }
 
-/// CDN purge tags request
-#[derive(Debug, Deserialize)]
-struct CdnPurgeTagsRequest {
-    tags: Vec<String>,
-}
+/// List all backups
+async fn list_backups_handler(
+    user: AuthUser,
+    Query(query): Query<BackupListQuery>,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let pool = state.db().inner();
+    let page = query.page.unwrap_or(1);
+    let per_page = query.per_page.unwrap_or(20);
+    let offset = ((page - 1) * per_page) as i64;
 
-/// CDN configuration request
-#[derive(Debug, Deserialize)]
-struct CdnConfigRequest {
-    provider: Option<String>,
-    api_key: Option<String>,
-    zone_id: Option<String>,
-    enabled: Option<bool>,
-}
-
-/// Get CDN status
-async fn cdn_status_handler(
-    user: AuthUser,
-    State(state): State<AppState>,
-) -> HttpResult<impl axum::response::IntoResponse> {
-    // Get CDN configuration from options
-    let pool = state.db().inner();
-    let cdn_enabled: Option<(String,)> =
-        sqlx::query_as("SELECT option_value::text FROM options WHERE option_name = 'cdn_enabled'")
-            .fetch_optional(pool)
-            .await
-            .unwrap_or(None);
+    // Get backups from database (using actual schema with file_size column)
+    let backups: Vec<(
+        Uuid,
+        String,
+        String,
+        Option<i64>,
+        String,
+        chrono::DateTime<chrono::Utc>,
+    )> = sqlx::query_as(
+        r#"
+        SELECT id, name, backup_type, file_size, status, created_at
+        FROM backups
+        ORDER BY created_at DESC
+        LIMIT $1 OFFSET $2
+        "#,
+    )
+    .bind(per_page as i64)
+    .bind(offset)
+    .fetch_all(pool)
+    .await
+    .unwrap_or_default();
 
-    let cdn_provider: Option<(String,)> =
-        sqlx::query_as("SELECT option_value::text FROM options WHERE option_name = 'cdn_provider'")
-            .fetch_optional(pool)
-            .await
-            .unwrap_or(None);
+    let total: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM backups")
+        .fetch_one(pool)
+        .await
+        .unwrap_or((0,));
 
-    let enabled = cdn_enabled.map(|v| v.0 == "true").unwrap_or(false);
-    let provider = cdn_provider
-        .map(|v| v.0)
-        .unwrap_or_else(|| "none".to_string());
+    let backup_list: Vec<serde_json::Value> = backups
+        .iter()
+        .map(|(id, name, backup_type, size, status, created_at)| {
+            serde_json::json!({
+                "id": id,
+                "name": name,
+                "type": backup_type,
+                "size_bytes": size.unwrap_or(0),
+                "status": status,
+                "created_at": created_at
+            })
+        })
+        .collect();
 
     Ok(json(serde_json::json!({
-        "enabled": enabled,
-        "provider": provider,
-        "status": if enabled { "active" } else { "inactive" },
-        "features": {
-            "purge_urls": true,
-            "purge_tags": provider == "cloudflare",
-            "purge_all": true,
-            "stats": provider != "none"
-        }
+        "backups": backup_list,
+        "total": total.0,
+        "page": page,
+        "per_page": per_page
     })))
 }
 
-/// Get CDN configuration
-async fn get_cdn_config_handler(
+/// Create backup request
+#[derive(Debug, Deserialize)]
+struct CreateBackupRequest {
+    #[serde(rename = "type")]
+    backup_type: Option<String>,
+    include_media: Option<bool>,
+    include_themes: Option<bool>,
+    include_plugins: Option<bool>,
+    description: Option<String>,
+}
+
+/// Create a new backup
+async fn create_backup_handler(
     user: AuthUser,
     State(state): State<AppState>,
+    Json(payload): Json<CreateBackupRequest>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
     let pool = state.db().inner();
+    let backup_id = Uuid::now_v7();
+    let backup_type = payload.backup_type.unwrap_or_else(|| "full".to_string());
+    let now = chrono::Utc::now();
+    let name = format!("backup_{}_{}", backup_type, now.format("%Y%m%d_%H%M%S"));
 
-    let options: Vec<(String, String)> = sqlx::query_as(
-        "SELECT option_name, option_value::text FROM options WHERE option_name LIKE 'cdn_%'",
+    // Build includes JSONB
+    let includes = serde_json::json!({
+        "database": true,
+        "media": payload.include_media.unwrap_or(true),
+        "themes": payload.include_themes.unwrap_or(true),
+        "plugins": payload.include_plugins.unwrap_or(true)
+    });
+
+    // Create backup record (matching actual schema)
+    sqlx::query(
+        r#"
+        INSERT INTO backups (id, name, backup_type, status, includes, rustpress_version, started_at, created_at)
+        VALUES ($1, $2, $3, 'pending', $4, '1.0.0', $5, $5)
+        "#
     )
-    .fetch_all(pool)
+    .bind(backup_id)
+    .bind(&name)
+    .bind(&backup_type)
+    .bind(&includes)
+    .bind(now)
+    .execute(pool)
     .await
-    .unwrap_or_default();
+    .map_err(|e| rustpress_core::error::Error::database_with_source("Failed to create backup", e))?;
 
-    let mut config = serde_json::Map::new();
-    for (key, value) in options {
-        let key = key.strip_prefix("cdn_").unwrap_or(&key);
-        // Mask sensitive values
-        if key == "api_key" || key == "api_token" {
-            config.insert(key.to_string(), serde_json::json!("***"));
-        } else {
-            config.insert(key.to_string(), serde_json::json!(value));
-        }
-    }
+    // Queue backup job (in production this would be a background job)
 
-    Ok(json(serde_json::json!({
-        "config": config,
-        "available_providers": ["cloudflare", "bunnycdn", "fastly", "keycdn"]
+    Ok(created(serde_json::json!({
+        "id": backup_id,
+        "name": name,
+        "type": backup_type,
+        "status": "pending",
+        "message": "Backup job has been queued"
     })))
 }
 
-/// Update CDN configuration
-async fn update_cdn_config_handler(
+/// Get a specific backup
+async fn get_backup_handler(
     user: AuthUser,
+    PathId(id): PathId,
     State(state): State<AppState>,
-    Json(payload): Json<CdnConfigRequest>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
     let pool = state.db().inner();
 
-    // Update each provided configuration option
-    if let Some(provider) = &payload.provider {
-        sqlx::query(
-            r#"
-            INSERT INTO options (option_name, option_value, autoload)
-            VALUES ('cdn_provider', $1::jsonb, true)
-            ON CONFLICT (option_name) WHERE site_id IS NULL
-            DO UPDATE SET option_value = $1::jsonb, updated_at = NOW()
-            "#,
-        )
-        .bind(serde_json::json!(provider))
-        .execute(pool)
-        .await
-        .ok();
-    }
+    let backup: Option<(
+        Uuid,
+        String,
+        String,
+        Option<i64>,
+        String,
+        chrono::DateTime<chrono::Utc>,
+    )> = sqlx::query_as(
+        "SELECT id, name, backup_type, file_size, status, created_at FROM backups WHERE id = $1",
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| rustpress_core::error::Error::database_with_source("Failed to get backup", e))?;
 
-    if let Some(api_key) = &payload.api_key {
-        sqlx::query(
-            r#"
-            INSERT INTO options (option_name, option_value, autoload)
-            VALUES ('cdn_api_key', $1::jsonb, false)
-            ON CONFLICT (option_name) WHERE site_id IS NULL
-            DO UPDATE SET option_value = $1::jsonb, updated_at = NOW()
-            "#,
-        )
-        .bind(serde_json::json!(api_key))
-        .execute(pool)
-        .await
-        .ok();
+    match backup {
+        Some((id, name, backup_type, size, status, created_at)) => Ok(json(serde_json::json!({
+            "id": id,
+            "name": name,
+            "type": backup_type,
+            "size_bytes": size.unwrap_or(0),
+            "status": status,
+            "created_at": created_at
+        }))),
+        None => Err(rustpress_core::error::Error::not_found("Backup", id.to_string()).into()),
     }
+}
 
-    if let Some(zone_id) = &payload.zone_id {
-        sqlx::query(
-            r#"
-            INSERT INTO options (option_name, option_value, autoload)
-            VALUES ('cdn_zone_id', $1::jsonb, false)
-            ON CONFLICT (option_name) WHERE site_id IS NULL
-            DO UPDATE SET option_value = $1::jsonb, updated_at = NOW()
-            "#,
-        )
-        .bind(serde_json::json!(zone_id))
-        .execute(pool)
-        .await
-        .ok();
-    }
+/// Delete a backup
+async fn delete_backup_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let pool = state.db().inner();
 
-    if let Some(enabled) = payload.enabled {
-        sqlx::query(
-            r#"
-            INSERT INTO options (option_name, option_value, autoload)
-            VALUES ('cdn_enabled', $1::jsonb, true)
-            ON CONFLICT (option_name) WHERE site_id IS NULL
-            DO UPDATE SET option_value = $1::jsonb, updated_at = NOW()
-            "#,
-        )
-        .bind(serde_json::json!(enabled))
+    // Delete the backup record (actual schema doesn't have deleted_at)
+    sqlx::query("DELETE FROM backups WHERE id = $1")
+        .bind(id)
         .execute(pool)
         .await
-        .ok();
-    }
+        .map_err(|e| {
+            rustpress_core::error::Error::database_with_source("Failed to delete backup", e)
+        })?;
 
-    Ok(json(serde_json::json!({
-        "success": true,
-        "message": "CDN configuration updated"
-    })))
+    Ok(no_content())
 }
 
-/// Purge all CDN cache
-async fn cdn_purge_all_handler(
+/// Download a backup
+async fn download_backup_handler(
     user: AuthUser,
+    PathId(id): PathId,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    // Also clear local cache
-    let cache = state.cache();
-    let _ = cache.clear().await;
+    let pool = state.db().inner();
 
-    // In production, would call CDN provider API
-    // For now, just return success
-    Ok(json(serde_json::json!({
-        "success": true,
-        "message": "CDN cache purge initiated",
-        "purged": {
-            "local_cache": true,
-            "cdn_cache": true
-        }
-    })))
-}
-
-/// Purge specific URLs from CDN
-async fn cdn_purge_urls_handler(
-    user: AuthUser,
-    State(state): State<AppState>,
-    Json(payload): Json<CdnPurgeUrlsRequest>,
-) -> HttpResult<impl axum::response::IntoResponse> {
-    let urls = &payload.urls;
+    let backup: Option<(String, Option<String>)> = sqlx::query_as(
+        "SELECT name, file_path FROM backups WHERE id = $1 AND status = 'completed'",
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| rustpress_core::error::Error::database_with_source("Failed to get backup", e))?;
 
-    if urls.is_empty() {
-        return Err(rustpress_core::error::Error::validation("No URLs provided for purge").into());
-    }
+    match backup {
+        Some((name, Some(file_path))) => {
+            // Read backup file
+            let contents = tokio::fs::read(&file_path).await.map_err(|e| {
+                rustpress_core::error::Error::internal(format!("Failed to read backup file: {}", e))
+            })?;
 
-    if urls.len() > 30 {
-        return Err(
-            rustpress_core::error::Error::validation("Maximum 30 URLs per purge request").into(),
-        );
-    }
+            let disposition = format!("attachment; filename=\"{}.zip\"", name);
 
-    // Clear related local cache entries
-    let cache = state.cache();
-    for url in urls {
-        // Create a cache key from URL
-        let key = format!(
-            "page:{}",
-            url.trim_start_matches("http://")
-                .trim_start_matches("https://")
-        );
-        let _ = cache.delete(&key).await;
+            Ok(axum::response::Response::builder()
+                .status(axum::http::StatusCode::OK)
+                .header(header::CONTENT_TYPE, "application/zip")
+                .header(header::CONTENT_DISPOSITION, disposition)
+                .body(axum::body::Body::from(contents))
+                .unwrap()
+                .into_response())
+        }
+        Some((_, None)) => Err(rustpress_core::error::Error::internal(
+            "Backup file path not set".to_string(),
+        )
+        .into()),
+        None => Err(rustpress_core::error::Error::not_found("Backup", id.to_string()).into()),
     }
-
-    // In production, would call CDN provider API
-    Ok(json(serde_json::json!({
-        "success": true,
-        "message": format!("{} URLs purged", urls.len()),
-        "urls_purged": urls.len()
-    })))
 }
 
-/// Purge by cache tags
-async fn cdn_purge_tags_handler(
+/// Restore from backup
+async fn restore_backup_handler(
     user: AuthUser,
+    PathId(id): PathId,
     State(state): State<AppState>,
-    Json(payload): Json<CdnPurgeTagsRequest>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let tags = &payload.tags;
-
-    if tags.is_empty() {
-        return Err(rustpress_core::error::Error::validation("No tags provided for purge").into());
-    }
-
-    // Clear local cache by tags
-    let cache = state.cache();
-    let mut total_purged = 0u64;
-    for tag in tags {
-        if let Ok(count) = cache.clear_by_tag(tag).await {
-            total_purged += count;
-        }
-    }
+    let job_id = Uuid::now_v7();
 
-    // In production, would call CDN provider API for tag-based purge
+    // In production, this would queue a restore job
     Ok(json(serde_json::json!({
-        "success": true,
-        "message": format!("{} tags purged", tags.len()),
-        "tags_purged": tags.len(),
-        "local_entries_cleared": total_purged
+        "job_id": job_id,
+        "status": "queued",
+        "message": "Restore job has been queued"
     })))
 }
 
-/// Get CDN statistics
-async fn cdn_stats_handler(
+/// Get restore progress
+async fn restore_progress_handler(
     user: AuthUser,
+    axum::extract::Path(job_id): axum::extract::Path<Uuid>,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    // In production, would fetch from CDN provider API
-    // Return placeholder stats for now
+    // In production, this would check the job status
     Ok(json(serde_json::json!({
-        "total_requests": 0,
-        "cached_requests": 0,
-        "cache_hit_ratio": 0.0,
-        "bandwidth_saved_bytes": 0,
-        "total_bandwidth_bytes": 0,
-        "threats_blocked": 0,
-        "period": {
-            "start": chrono::Utc::now() - chrono::Duration::days(7),
-            "end": chrono::Utc::now()
-        },
-        "note": "Connect a CDN provider to view real statistics"
+        "job_id": job_id,
+        "status": "completed",
+        "progress": 100,
+        "message": "Restore completed successfully"
     })))
 }
 
-/// CDN health check
-async fn cdn_health_handler(
+/// Get backup storage usage
+async fn backup_storage_handler(
+    user: AuthUser,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
     let pool = state.db().inner();
 
-    let cdn_enabled: Option<(String,)> =
-        sqlx::query_as("SELECT option_value::text FROM options WHERE option_name = 'cdn_enabled'")
-            .fetch_optional(pool)
-            .await
-            .unwrap_or(None);
+    let total_size: (i64,) = sqlx::query_as("SELECT COALESCE(SUM(file_size), 0) FROM backups")
+        .fetch_one(pool)
+        .await
+        .unwrap_or((0,));
 
-    let enabled = cdn_enabled.map(|v| v.0 == "true").unwrap_or(false);
+    let backup_count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM backups")
+        .fetch_one(pool)
+        .await
+        .unwrap_or((0,));
 
     Ok(json(serde_json::json!({
-        "status": if enabled { "healthy" } else { "not_configured" },
-        "cdn_enabled": enabled,
-        "local_cache_healthy": true,
-        "details": {
-            "provider_reachable": enabled,
-            "ssl_valid": true,
-            "last_purge": null
-        }
+        "used_bytes": total_size.0,
+        "backup_count": backup_count.0,
+        "quota_bytes": 10_737_418_240i64, // 10GB default quota
+        "used_percentage": (total_size.0 as f64 / 10_737_418_240.0 * 100.0).round()
     })))
 }
 
-// =============================================================================
-// Taxonomy Routes and Handlers (Categories, Tags)
-// =============================================================================
-
-/// Taxonomy management routes
-fn taxonomy_routes() -> Router<AppState> {
-    Router::new()
-        .route(
-            "/categories",
-            get(list_categories_handler).post(create_category_handler),
-        )
-        .route(
-            "/categories/:id",
-            get(get_category_handler)
-                .put(update_category_handler)
-                .delete(delete_category_handler),
-        )
-        .route("/tags", get(list_tags_handler).post(create_tag_handler))
-        .route(
-            "/tags/:id",
-            get(get_tag_handler)
-                .put(update_tag_handler)
-                .delete(delete_tag_handler),
-        )
-}
-
-/// List categories
-async fn list_categories_handler(
+/// List backup schedules
+async fn list_backup_schedules_handler(
+    user: AuthUser,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
     let pool = state.db().inner();
 
-    let categories: Vec<(Uuid, String, String, Option<String>, Option<Uuid>, i32)> = sqlx::query_as(
-        r#"
-        SELECT id, name, slug, description, parent_id,
-               (SELECT COUNT(*) FROM post_categories WHERE category_id = categories.id)::int as post_count
-        FROM categories
-        ORDER BY name
-        "#
+    // Using actual schema: frequency + cron_expression instead of schedule
+    let schedules: Vec<(Uuid, String, String, Option<String>, String, bool, Option<chrono::DateTime<chrono::Utc>>)> = sqlx::query_as(
+        "SELECT id, name, frequency, cron_expression, backup_type, enabled, next_run FROM backup_schedules"
     )
     .fetch_all(pool)
     .await
     .unwrap_or_default();
 
-    let cat_list: Vec<serde_json::Value> = categories
+    let schedule_list: Vec<serde_json::Value> = schedules
         .iter()
-        .map(|(id, name, slug, desc, parent, count)| {
-            serde_json::json!({
-                "id": id,
-                "name": name,
-                "slug": slug,
-                "description": desc,
-                "parent_id": parent,
-                "post_count": count
-            })
-        })
+        .map(
+            |(id, name, frequency, cron_expr, backup_type, enabled, next_run)| {
+                serde_json::json!({
+                    "id": id,
+                    "name": name,
+                    "frequency": frequency,
+                    "schedule": cron_expr.clone().unwrap_or_else(|| frequency.clone()),
+                    "type": backup_type,
+                    "enabled": enabled,
+                    "next_run_at": next_run
+                })
+            },
+        )
         .collect();
 
-    Ok(json(serde_json::json!({ "categories": cat_list })))
+    Ok(json(serde_json::json!({ "schedules": schedule_list })))
 }
 
-/// Create category request
+/// Create backup schedule request
 #[derive(Debug, Deserialize)]
-struct CreateCategoryRequest {
+struct CreateBackupScheduleRequest {
     name: String,
-    slug: Option<String>,
-    description: Option<String>,
-    parent_id: Option<Uuid>,
+    schedule: String, // cron expression or frequency
+    #[serde(rename = "type")]
+    backup_type: Option<String>,
+    retention_days: Option<i32>,
 }
 
-/// Create category
-async fn create_category_handler(
+/// Create a backup schedule
+async fn create_backup_schedule_handler(
     user: AuthUser,
     State(state): State<AppState>,
-    Json(payload): Json<CreateCategoryRequest>,
+    Json(payload): Json<CreateBackupScheduleRequest>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
     let pool = state.db().inner();
-    let id = Uuid::now_v7();
-    let slug = payload
-        .slug
-        .unwrap_or_else(|| slugify::slugify(&payload.name, "-", "", None));
+    let schedule_id = Uuid::now_v7();
+    let backup_type = payload.backup_type.unwrap_or_else(|| "full".to_string());
     let now = chrono::Utc::now();
 
+    // Build retention JSONB
+    let retention = serde_json::json!({
+        "retention_days": payload.retention_days.unwrap_or(30),
+        "max_backups": 10,
+        "min_backups": 1
+    });
+
     sqlx::query(
         r#"
-        INSERT INTO categories (id, name, slug, description, parent_id, created_at, updated_at)
-        VALUES ($1, $2, $3, $4, $5, $6, $6)
-        "#,
-    )
-    .bind(id)
+        INSERT INTO backup_schedules (id, name, frequency, cron_expression, backup_type, retention, enabled, created_at, updated_at)
+        VALUES ($1, $2, 'custom', $3, $4, $5, true, $6, $6)
+        "#
+    )
+    .bind(schedule_id)
     .bind(&payload.name)
-    .bind(&slug)
-    .bind(&payload.description)
-    .bind(payload.parent_id)
+    .bind(&payload.schedule)
+    .bind(&backup_type)
+    .bind(&retention)
     .bind(now)
     .execute(pool)
     .await
-    .map_err(|e| {
-        rustpress_core::error::Error::database_with_source("Failed to create category", e)
-    })?;
+    .map_err(|e| rustpress_core::error::Error::database_with_source("Failed to create schedule", e))?;
 
     Ok(created(serde_json::json!({
-        "id": id,
+        "id": schedule_id,
         "name": payload.name,
-        "slug": slug
+        "schedule": payload.schedule,
+        "type": backup_type,
+        "enabled": true
     })))
 }
 
-/// Get category
-async fn get_category_handler(
+/// Get backup schedule
+async fn get_backup_schedule_handler(
+    user: AuthUser,
     PathId(id): PathId,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
-
-    let category: Option<(Uuid, String, String, Option<String>, Option<Uuid>)> = sqlx::query_as(
-        "SELECT id, name, slug, description, parent_id FROM categories WHERE id = $1",
-    )
-    .bind(id)
-    .fetch_optional(pool)
-    .await
-    .map_err(|e| rustpress_core::error::Error::database_with_source("Failed to get category", e))?;
-
-    match category {
-        Some((id, name, slug, desc, parent)) => Ok(json(serde_json::json!({
-            "id": id, "name": name, "slug": slug, "description": desc, "parent_id": parent
-        }))),
-        None => Err(rustpress_core::error::Error::not_found("Category", id.to_string()).into()),
-    }
+    Ok(json(serde_json::json!({ "id": id })))
 }
 
-/// Update category
-async fn update_category_handler(
+/// Update backup schedule
+async fn update_backup_schedule_handler(
     user: AuthUser,
     PathId(id): PathId,
     State(state): State<AppState>,
     Json(payload): Json<serde_json::Value>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
-
-    if let Some(name) = payload.get("name").and_then(|v| v.as_str()) {
-        sqlx::query("UPDATE categories SET name = $1, updated_at = NOW() WHERE id = $2")
-            .bind(name)
-            .bind(id)
-            .execute(pool)
-            .await
-            .ok();
-    }
-
     Ok(json(serde_json::json!({ "id": id, "updated": true })))
 }
 
-/// Delete category
-async fn delete_category_handler(
+/// Delete backup schedule
+async fn delete_backup_schedule_handler(
     user: AuthUser,
     PathId(id): PathId,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
     let pool = state.db().inner();
 
-    sqlx::query("DELETE FROM categories WHERE id = $1")
+    sqlx::query("DELETE FROM backup_schedules WHERE id = $1")
         .bind(id)
         .execute(pool)
         .await
         .map_err(|e| {
-            rustpress_core::error::Error::database_with_source("Failed to delete category", e)
+            rustpress_core::error::Error::database_with_source("Failed to delete schedule", e)
         })?;
 
     Ok(no_content())
 }
 
-/// List tags
-async fn list_tags_handler(
-    State(state): State<AppState>,
-) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
-
-    let tags: Vec<(Uuid, String, String, Option<String>, i32)> = sqlx::query_as(
-        r#"
-        SELECT id, name, slug, description,
-               (SELECT COUNT(*) FROM post_tags WHERE tag_id = tags.id)::int as post_count
-        FROM tags
-        ORDER BY name
-        "#,
-    )
-    .fetch_all(pool)
-    .await
-    .unwrap_or_default();
-
-    let tag_list: Vec<serde_json::Value> = tags
-        .iter()
-        .map(|(id, name, slug, desc, count)| {
-            serde_json::json!({
-                "id": id,
-                "name": name,
-                "slug": slug,
-                "description": desc,
-                "post_count": count
-            })
-        })
-        .collect();
-
-    Ok(json(serde_json::json!({ "tags": tag_list })))
-}
+// =============================================================================
+// SEO Routes and Handlers
+// =============================================================================
 
-/// Create tag request
-#[derive(Debug, Deserialize)]
-struct CreateTagRequest {
-    name: String,
-    slug: Option<String>,
-    description: Option<String>,
+/// SEO management routes
+fn seo_routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/settings",
+            get(get_seo_settings_handler).put(update_seo_settings_handler),
+        )
+        .route("/sitemap", get(get_sitemap_status_handler))
+        .route("/sitemap/generate", post(generate_sitemap_handler))
+        .route(
+            "/robots",
+            get(get_robots_txt_handler).put(update_robots_txt_handler),
+        )
+        .route(
+            "/regions",
+            get(get_region_mapping_handler).put(update_region_mapping_handler),
+        )
+        .route("/regions/validate", post(validate_hreflang_handler))
+        .route(
+            "/indexing",
+            get(get_indexing_settings_handler).put(update_indexing_settings_handler),
+        )
+        .route("/indexing/submit", post(submit_indexing_urls_handler))
+        .route("/indexing/report", get(indexing_report_handler))
+        .route("/indexing/submissions", get(indexing_submissions_handler))
+        .route("/analyze", post(analyze_seo_handler))
+        .route("/bulk-analyze", post(bulk_analyze_seo_handler))
+        .route("/dashboard", get(seo_dashboard_handler))
+        .route("/structured-data", get(structured_data_audit_handler))
+        .route(
+            "/:content_type/:id",
+            get(get_content_seo_handler).put(update_content_seo_handler),
+        )
 }
 
-/// Create tag
-async fn create_tag_handler(
+/// Get SEO settings
+async fn get_seo_settings_handler(
     user: AuthUser,
     State(state): State<AppState>,
-    Json(payload): Json<CreateTagRequest>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
     let pool = state.db().inner();
-    let id = Uuid::now_v7();
-    let slug = payload
-        .slug
-        .unwrap_or_else(|| slugify::slugify(&payload.name, "-", "", None));
-    let now = chrono::Utc::now();
 
-    sqlx::query(
-        r#"
-        INSERT INTO tags (id, name, slug, description, created_at, updated_at)
-        VALUES ($1, $2, $3, $4, $5, $5)
-        "#,
+    // Get SEO-related settings
+    let settings: Vec<(String, serde_json::Value)> = sqlx::query_as(
+        "SELECT key, value FROM settings WHERE key LIKE 'seo_%' OR key LIKE 'site_%'",
     )
-    .bind(id)
-    .bind(&payload.name)
-    .bind(&slug)
-    .bind(&payload.description)
-    .bind(now)
-    .execute(pool)
+    .fetch_all(pool)
     .await
-    .map_err(|e| rustpress_core::error::Error::database_with_source("Failed to create tag", e))?;
-
-    Ok(created(serde_json::json!({
-        "id": id,
-        "name": payload.name,
-        "slug": slug
-    })))
-}
-
-/// Get tag
-async fn get_tag_handler(
-    PathId(id): PathId,
-    State(state): State<AppState>,
-) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
+    .unwrap_or_default();
 
-    let tag: Option<(Uuid, String, String, Option<String>)> =
-        sqlx::query_as("SELECT id, name, slug, description FROM tags WHERE id = $1")
-            .bind(id)
-            .fetch_optional(pool)
-            .await
-            .map_err(|e| {
-                rustpress_core::error::Error::database_with_source("Failed to get tag", e)
-            })?;
+    let mut seo_settings = serde_json::json!({
+        "site_title": "",
+        "site_description": "",
+        "title_separator": " | ",
+        "default_meta_robots": "index, follow",
+        "enable_open_graph": true,
+        "enable_twitter_cards": true,
+        "enable_schema_markup": true,
+        "google_analytics_id": "",
+        "google_search_console": "",
+        "bing_webmaster": ""
+    });
 
-    match tag {
-        Some((id, name, slug, desc)) => Ok(json(serde_json::json!({
-            "id": id, "name": name, "slug": slug, "description": desc
-        }))),
-        None => Err(rustpress_core::error::Error::not_found("Tag", id.to_string()).into()),
+    for (key, value) in settings {
+        if let serde_json::Value::Object(ref mut map) = seo_settings {
+            map.insert(key, value);
+        }
     }
+
+    Ok(json(seo_settings))
 }
 
-/// Update tag
-async fn update_tag_handler(
+/// Update SEO settings
+async fn update_seo_settings_handler(
     user: AuthUser,
-    PathId(id): PathId,
     State(state): State<AppState>,
     Json(payload): Json<serde_json::Value>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
     let pool = state.db().inner();
 
-    if let Some(name) = payload.get("name").and_then(|v| v.as_str()) {
-        sqlx::query("UPDATE tags SET name = $1, updated_at = NOW() WHERE id = $2")
-            .bind(name)
-            .bind(id)
+    if let Some(obj) = payload.as_object() {
+        for (key, value) in obj {
+            sqlx::query(
+                r#"
+                INSERT INTO settings (id, key, value, group_name, updated_at)
+                VALUES (gen_random_uuid(), $1, $2, 'seo', NOW())
+                ON CONFLICT (key) DO UPDATE SET value = $2, updated_at = NOW()
+                "#,
+            )
+            .bind(key)
+            .bind(value)
             .execute(pool)
             .await
             .ok();
+        }
     }
 
-    Ok(json(serde_json::json!({ "id": id, "updated": true })))
+    Ok(json(serde_json::json!({ "success": true })))
 }
 
-/// Delete tag
-async fn delete_tag_handler(
-    user: AuthUser,
-    PathId(id): PathId,
+/// Get sitemap status
+async fn get_sitemap_status_handler(
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
     let pool = state.db().inner();
 
-    sqlx::query("DELETE FROM tags WHERE id = $1")
-        .bind(id)
-        .execute(pool)
-        .await
-        .map_err(|e| {
-            rustpress_core::error::Error::database_with_source("Failed to delete tag", e)
-        })?;
+    let post_count: (i64,) = sqlx::query_as(&format!(
+        "SELECT COUNT(*) FROM posts WHERE post_type = 'post' AND status = 'published' \
+         AND deleted_at IS NULL AND {}",
+        site_filter("site_id")
+    ))
+    .fetch_one(pool)
+    .await
+    .unwrap_or((0,));
 
-    Ok(no_content())
+    let page_count: (i64,) = sqlx::query_as(&format!(
+        "SELECT COUNT(*) FROM posts WHERE post_type = 'page' AND status = 'published' \
+         AND deleted_at IS NULL AND {}",
+        site_filter("site_id")
+    ))
+    .fetch_one(pool)
+    .await
+    .unwrap_or((0,));
+
+    Ok(json(serde_json::json!({
+        "url_count": post_count.0 + page_count.0 + 2, // +2 for home and blog
+        "last_generated": chrono::Utc::now(),
+        "sitemap_url": "/sitemap.xml"
+    })))
 }
 
-// =============================================================================
-// Menu Routes and Handlers
-// =============================================================================
-
-/// Menu management routes
-fn menu_routes() -> Router<AppState> {
-    Router::new()
-        .route("/", get(list_menus_handler).post(create_menu_handler))
-        .route("/locations", get(list_menu_locations_handler))
-        .route(
-            "/:id",
-            get(get_menu_handler)
-                .put(update_menu_handler)
-                .delete(delete_menu_handler),
-        )
-        .route(
-            "/:id/items",
-            get(get_menu_items_handler).put(update_menu_items_handler),
-        )
-}
-
-/// List menus
-async fn list_menus_handler(
+/// Generate sitemap
+async fn generate_sitemap_handler(
+    user: AuthUser,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
-
-    let menus: Vec<(Uuid, String, String, Option<String>)> = sqlx::query_as(
-        "SELECT id, name, slug, location FROM menus WHERE deleted_at IS NULL ORDER BY name",
-    )
-    .fetch_all(pool)
-    .await
-    .unwrap_or_default();
-
-    let menu_list: Vec<serde_json::Value> = menus
-        .iter()
-        .map(|(id, name, slug, location)| {
-            serde_json::json!({
-                "id": id,
-                "name": name,
-                "slug": slug,
-                "location": location
-            })
-        })
-        .collect();
-
-    Ok(json(serde_json::json!({ "menus": menu_list })))
+    // In production, this would regenerate the sitemap
+    Ok(json(serde_json::json!({
+        "success": true,
+        "message": "Sitemap regenerated successfully"
+    })))
 }
 
-/// List available menu locations
-async fn list_menu_locations_handler(
-    State(_state): State<AppState>,
+/// Get the managed robots.txt configuration and the rendered file
+async fn get_robots_txt_handler(
+    _user: AuthUser,
+    State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    // Return standard theme menu locations
+    let renderer = state.renderer();
+    let config = renderer.robots_config().await;
+    let environment = current_environment();
+
     Ok(json(serde_json::json!({
-        "locations": [
-            { "slug": "primary", "name": "Primary Navigation", "description": "Main site navigation menu" },
-            { "slug": "secondary", "name": "Secondary Navigation", "description": "Secondary navigation menu" },
-            { "slug": "footer", "name": "Footer Menu", "description": "Footer navigation links" },
-            { "slug": "social", "name": "Social Links", "description": "Social media links menu" },
-            { "slug": "mobile", "name": "Mobile Menu", "description": "Mobile-specific navigation" }
-        ]
+        "content": config.render(&environment, &renderer.site_url().await),
+        "environment": environment,
+        "indexing_blocked": config.blocks_indexing(&environment),
+        "config": config.as_ref(),
     })))
 }
 
-/// Create menu request
+/// Update robots.txt request: a full configuration, or only custom rules
+/// (the legacy `content` field)
 #[derive(Debug, Deserialize)]
-struct CreateMenuRequest {
-    name: String,
-    slug: Option<String>,
-    location: Option<String>,
+struct UpdateRobotsRequest {
+    config: Option<RobotsConfig>,
+    content: Option<String>,
 }
 
-/// Create menu
-async fn create_menu_handler(
+/// Update the managed robots.txt configuration
+async fn update_robots_txt_handler(
     user: AuthUser,
     State(state): State<AppState>,
-    Json(payload): Json<CreateMenuRequest>,
+    Json(payload): Json<UpdateRobotsRequest>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    // Validate name is not empty
-    if payload.name.trim().is_empty() {
-        return Err(rustpress_core::error::Error::validation("Menu name cannot be empty").into());
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can change robots.txt",
+        ));
     }
 
-    let pool = state.db().inner();
-    let id = Uuid::now_v7();
-    let slug = payload
-        .slug
-        .filter(|s| !s.trim().is_empty())
-        .unwrap_or_else(|| {
-            // Use a safe slug generation - fallback to UUID if slugify fails
-            let base_slug = payload
-                .name
-                .to_lowercase()
-                .chars()
-                .map(|c| if c.is_alphanumeric() { c } else { '-' })
-                .collect::<String>();
-            let cleaned: String = base_slug
-                .split('-')
-                .filter(|s| !s.is_empty())
-                .collect::<Vec<_>>()
-                .join("-");
-            if cleaned.is_empty() {
-                format!("menu-{}", &id.to_string()[..8])
-            } else {
-                cleaned
-            }
-        });
-    let now = chrono::Utc::now();
+    let renderer = state.renderer();
+    let config = match payload.config {
+        Some(config) => config,
+        None => {
+            let mut config = renderer.robots_config().await.as_ref().clone();
+            config.custom_rules = payload.content;
+            config
+        }
+    };
 
-    sqlx::query(
-        "INSERT INTO menus (id, name, slug, location, created_at, updated_at) VALUES ($1, $2, $3, $4, $5, $5)"
-    )
-    .bind(id)
-    .bind(&payload.name)
-    .bind(&slug)
-    .bind(&payload.location)
-    .bind(now)
-    .execute(pool)
-    .await
-    .map_err(|e| rustpress_core::error::Error::database_with_source("Failed to create menu", e))?;
+    config.validate()?;
+    config.save(state.db().inner()).await?;
 
-    Ok(created(
-        serde_json::json!({ "id": id, "name": payload.name, "slug": slug }),
+    let content = config.render(&current_environment(), &renderer.site_url().await);
+    renderer.set_robots_config(config).await;
+
+    Ok(json(
+        serde_json::json!({ "success": true, "content": content }),
     ))
 }
 
-/// Get menu
-async fn get_menu_handler(
-    PathId(id): PathId,
+/// Get the multi-region variant mapping and any problems with it
+async fn get_region_mapping_handler(
+    _user: AuthUser,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
-
-    let menu: Option<(Uuid, String, String, Option<String>)> = sqlx::query_as(
-        "SELECT id, name, slug, location FROM menus WHERE id = $1 AND deleted_at IS NULL",
-    )
-    .bind(id)
-    .fetch_optional(pool)
-    .await
-    .map_err(|e| rustpress_core::error::Error::database_with_source("Failed to get menu", e))?;
-
-    match menu {
-        Some((id, name, slug, location)) => Ok(json(serde_json::json!({
-            "id": id, "name": name, "slug": slug, "location": location
-        }))),
-        None => Err(rustpress_core::error::Error::not_found("Menu", id.to_string()).into()),
-    }
+    let mapping = state.renderer().region_mapping().await;
+    Ok(json(serde_json::json!({
+        "mapping": mapping.as_ref(),
+        "issues": mapping.validate(),
+    })))
 }
 
-/// Update menu
-async fn update_menu_handler(
+/// Replace the multi-region variant mapping
+async fn update_region_mapping_handler(
     user: AuthUser,
-    PathId(id): PathId,
     State(state): State<AppState>,
-    Json(payload): Json<serde_json::Value>,
+    Json(mapping): Json<rustpress_content::regions::RegionMapping>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
-
-    if let Some(name) = payload.get("name").and_then(|v| v.as_str()) {
-        sqlx::query("UPDATE menus SET name = $1, updated_at = NOW() WHERE id = $2")
-            .bind(name)
-            .bind(id)
-            .execute(pool)
-            .await
-            .ok();
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can change region mapping",
+        ));
     }
 
-    Ok(json(serde_json::json!({ "id": id, "updated": true })))
-}
-
-/// Delete menu
-async fn delete_menu_handler(
-    user: AuthUser,
-    PathId(id): PathId,
-    State(state): State<AppState>,
-) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
-
-    sqlx::query("UPDATE menus SET deleted_at = NOW() WHERE id = $1")
-        .bind(id)
-        .execute(pool)
-        .await
-        .map_err(|e| {
-            rustpress_core::error::Error::database_with_source("Failed to delete menu", e)
-        })?;
-
-    Ok(no_content())
-}
-
-/// Get menu items
-async fn get_menu_items_handler(
-    PathId(id): PathId,
-    State(state): State<AppState>,
-) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
-
-    let items: Vec<(
-        Uuid,
-        String,
-        Option<String>,
-        Option<Uuid>,
-        i32,
-        String,
-        Option<String>,
-        String,
-        Option<String>,
-        bool,
-    )> = sqlx::query_as(
-        r#"
-        SELECT id, title, url, parent_id, position, item_type, target,
-               COALESCE(css_classes, '') as css_classes, icon, is_visible
-        FROM menu_items
-        WHERE menu_id = $1
-        ORDER BY position, created_at
-        "#,
-    )
-    .bind(id)
-    .fetch_all(pool)
-    .await
-    .unwrap_or_default();
-
-    let item_list: Vec<serde_json::Value> = items
-        .iter()
-        .map(
-            |(id, title, url, parent, pos, item_type, target, css_classes, icon, is_visible)| {
-                serde_json::json!({
-                    "id": id,
-                    "title": title,
-                    "url": url,
-                    "parent_id": parent,
-                    "position": pos,
-                    "item_type": item_type,
-                    "target": target,
-                    "css_classes": css_classes,
-                    "icon": icon,
-                    "is_visible": is_visible
-                })
-            },
-        )
-        .collect();
-
-    Ok(json(serde_json::json!({ "items": item_list })))
-}
-
-/// Menu item input for creating/updating menu items
-#[derive(Debug, Deserialize)]
-struct MenuItemInput {
-    id: Option<Uuid>,
-    title: String,
-    url: Option<String>,
-    parent_id: Option<Uuid>,
-    #[serde(default)]
-    position: i32,
-    #[serde(default = "default_item_type")]
-    item_type: String,
-    target: Option<String>,
-    css_classes: Option<String>,
-    icon: Option<String>,
-    #[serde(default = "default_true")]
-    is_visible: bool,
-    object_id: Option<Uuid>,
-    object_type: Option<String>,
-}
-
-fn default_item_type() -> String {
-    "custom".to_string()
-}
+    crate::services::regions::save_region_mapping(state.db().inner(), &mapping).await?;
+    state.renderer().set_region_mapping(mapping.clone()).await;
+    state.page_cache.purge_all().await?;
 
-fn default_true() -> bool {
-    true
+    Ok(json(mapping))
 }
 
+/// Hreflang validation request: the links found on each crawled page
 #[derive(Debug, Deserialize)]
-struct UpdateMenuItemsPayload {
-    items: Vec<MenuItemInput>,
+struct ValidateHreflangRequest {
+    pages: std::collections::HashMap<String, Vec<rustpress_content::regions::HreflangLink>>,
 }
 
-/// Update menu items - replaces all items for a menu
-async fn update_menu_items_handler(
+/// Check hreflang reciprocity, codes and x-default across crawled pages,
+/// together with the configured mapping
+async fn validate_hreflang_handler(
     _user: AuthUser,
-    PathId(menu_id): PathId,
     State(state): State<AppState>,
-    Json(payload): Json<UpdateMenuItemsPayload>,
+    Json(payload): Json<ValidateHreflangRequest>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
-
-    // Verify menu exists
-    let menu_exists: Option<(Uuid,)> =
-        sqlx::query_as("SELECT id FROM menus WHERE id = $1 AND deleted_at IS NULL")
-            .bind(menu_id)
-            .fetch_optional(pool)
-            .await
-            .map_err(|e| {
-                rustpress_core::error::Error::database_with_source("Failed to verify menu", e)
-            })?;
-
-    if menu_exists.is_none() {
-        return Err(rustpress_core::error::Error::not_found("Menu", menu_id.to_string()).into());
-    }
-
-    // Delete existing menu items
-    sqlx::query("DELETE FROM menu_items WHERE menu_id = $1")
-        .bind(menu_id)
-        .execute(pool)
-        .await
-        .map_err(|e| {
-            rustpress_core::error::Error::database_with_source("Failed to clear menu items", e)
-        })?;
-
-    // Insert new items
-    let mut inserted_items: Vec<serde_json::Value> = Vec::new();
-
-    for (idx, item) in payload.items.iter().enumerate() {
-        let item_id = item.id.unwrap_or_else(Uuid::now_v7);
-        let position = if item.position == 0 {
-            idx as i32
-        } else {
-            item.position
-        };
-        let url = item.url.clone().unwrap_or_default();
-        let target = item.target.clone().unwrap_or_else(|| "_self".to_string());
-
-        let result: (Uuid,) = sqlx::query_as(
-            r#"
-            INSERT INTO menu_items (
-                id, menu_id, title, url, parent_id, position, item_type,
-                target, css_classes, icon, is_visible, object_id, object_type
-            )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
-            RETURNING id
-            "#,
-        )
-        .bind(item_id)
-        .bind(menu_id)
-        .bind(&item.title)
-        .bind(&url)
-        .bind(item.parent_id)
-        .bind(position)
-        .bind(&item.item_type)
-        .bind(&target)
-        .bind(&item.css_classes)
-        .bind(&item.icon)
-        .bind(item.is_visible)
-        .bind(item.object_id)
-        .bind(&item.object_type)
-        .fetch_one(pool)
-        .await
-        .map_err(|e| {
-            rustpress_core::error::Error::database_with_source("Failed to insert menu item", e)
-        })?;
-
-        inserted_items.push(serde_json::json!({
-            "id": result.0,
-            "title": item.title,
-            "url": url,
-            "parent_id": item.parent_id,
-            "position": position,
-            "item_type": item.item_type,
-            "target": target,
-            "css_classes": item.css_classes,
-            "icon": item.icon,
-            "is_visible": item.is_visible
-        }));
-    }
+    let mut issues = state.renderer().region_mapping().await.validate();
+    issues.extend(rustpress_content::regions::validate_hreflang(
+        &payload.pages,
+    ));
 
     Ok(json(serde_json::json!({
-        "menu_id": menu_id,
-        "items": inserted_items,
-        "count": inserted_items.len()
+        "valid": issues.is_empty(),
+        "pages": payload.pages.len(),
+        "issues": issues,
     })))
 }
 
-// =============================================================================
-// Widget Routes and Handlers
-// =============================================================================
-
-/// Widget management routes
-fn widget_routes() -> Router<AppState> {
-    Router::new()
-        .route("/", get(list_widgets_handler))
-        .route("/types", get(list_widget_types_handler))
-        .route("/areas", get(list_widget_areas_handler))
-        .route(
-            "/areas/:area_id",
-            get(get_widget_area_handler).put(update_widget_area_handler),
-        )
-        .route(
-            "/:id",
-            get(get_widget_handler)
-                .put(update_widget_handler)
-                .delete(delete_widget_handler),
-        )
-}
+use crate::services::{IndexingConfig, IndexingReportQuery, SubmitUrlsRequest};
 
-/// List available widgets
-async fn list_widgets_handler(
+/// Search engine indexing and verification settings, private key masked
+async fn get_indexing_settings_handler(
+    user: AuthUser,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    // Return available widget types
+    require_indexing_admin(&user)?;
+    let config = state.indexing.config().await;
     Ok(json(serde_json::json!({
-        "widgets": [
-            { "type": "text", "name": "Text", "description": "Arbitrary text or HTML" },
-            { "type": "recent_posts", "name": "Recent Posts", "description": "Display recent posts" },
-            { "type": "categories", "name": "Categories", "description": "Display category list" },
-            { "type": "tags", "name": "Tag Cloud", "description": "Display tag cloud" },
-            { "type": "search", "name": "Search", "description": "Search form" },
-            { "type": "custom_html", "name": "Custom HTML", "description": "Custom HTML content" },
-            { "type": "navigation", "name": "Navigation Menu", "description": "Display a menu" }
-        ]
+        "config": config.masked(),
+        "key_location": config
+            .indexnow_enabled()
+            .then_some(INDEXNOW_KEY_PATH),
     })))
 }
 
-/// List available widget types
-async fn list_widget_types_handler(
-    State(_state): State<AppState>,
+/// Update search engine indexing and verification settings
+async fn update_indexing_settings_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(config): Json<IndexingConfig>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    Ok(json(serde_json::json!({
-        "types": [
-            { "type": "text", "name": "Text", "description": "Arbitrary text or HTML" },
-            { "type": "recent_posts", "name": "Recent Posts", "description": "Display recent posts" },
-            { "type": "categories", "name": "Categories", "description": "Display category list" },
-            { "type": "tags", "name": "Tag Cloud", "description": "Display tag cloud" },
-            { "type": "search", "name": "Search", "description": "Search form" },
-            { "type": "custom_html", "name": "Custom HTML", "description": "Custom HTML content" },
-            { "type": "navigation", "name": "Navigation Menu", "description": "Display a menu" }
-        ]
-    })))
+    require_indexing_admin(&user)?;
+    let config = state.indexing.update_config(config).await?;
+    Ok(json(config))
 }
 
-/// List widget areas/sidebars
-async fn list_widget_areas_handler(
+/// Queue URLs of the site for the next submission
+async fn submit_indexing_urls_handler(
+    user: AuthUser,
     State(state): State<AppState>,
+    Json(payload): Json<SubmitUrlsRequest>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
-
-    let areas: Vec<(Uuid, String, String, Option<String>)> = sqlx::query_as(
-        "SELECT id, slug, name, description FROM widget_areas WHERE deleted_at IS NULL ORDER BY name"
+    require_indexing_admin(&user)?;
+    let queued = state.indexing.queue_urls(payload).await?;
+    Ok(
+        Accepted::new(format!("{} URL(s) queued for search engines", queued))
+            .with_data(serde_json::json!({ "queued": queued }))
+            .with_status_url("/api/v1/seo/indexing/report?state=pending"),
     )
-    .fetch_all(pool)
-    .await
-    .unwrap_or_default();
+}
 
-    let area_list: Vec<serde_json::Value> = areas
-        .iter()
-        .map(|(id, slug, name, desc)| {
-            serde_json::json!({
-                "id": id,
-                "slug": slug,
-                "name": name,
-                "description": desc
-            })
-        })
-        .collect();
+/// Indexing state of each URL and counts by state
+async fn indexing_report_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Query(query): Query<IndexingReportQuery>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_indexing_admin(&user)?;
+    Ok(json(state.indexing.report(&query).await?))
+}
 
-    Ok(json(serde_json::json!({ "areas": area_list })))
+#[derive(Debug, Deserialize)]
+struct IndexingSubmissionsQuery {
+    limit: Option<i64>,
 }
 
-/// Get widget area with widgets
-async fn get_widget_area_handler(
-    axum::extract::Path(area_id): axum::extract::Path<String>,
+/// Requests made to search engines and their responses
+async fn indexing_submissions_handler(
+    user: AuthUser,
     State(state): State<AppState>,
+    Query(query): Query<IndexingSubmissionsQuery>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
+    require_indexing_admin(&user)?;
+    Ok(json(state.indexing.submissions(query.limit).await?))
+}
 
-    let area: Option<(Uuid, String, String)> = sqlx::query_as(
-        "SELECT id, slug, name FROM widget_areas WHERE slug = $1 AND deleted_at IS NULL",
-    )
-    .bind(&area_id)
-    .fetch_optional(pool)
-    .await
-    .ok()
-    .flatten();
+fn require_indexing_admin(user: &AuthUser) -> HttpResult<()> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can manage search engine indexing",
+        ));
+    }
+    Ok(())
+}
 
-    match area {
-        Some((id, slug, name)) => {
-            let widgets: Vec<(Uuid, String, String, serde_json::Value, i32)> = sqlx::query_as(
-                r#"
-                SELECT id, widget_type, title, settings, position
-                FROM widgets
-                WHERE area_id = $1 AND deleted_at IS NULL
-                ORDER BY position
-                "#,
-            )
-            .bind(id)
-            .fetch_all(pool)
-            .await
-            .unwrap_or_default();
-
-            let widget_list: Vec<serde_json::Value> = widgets
-                .iter()
-                .map(|(id, wtype, title, settings, pos)| {
-                    serde_json::json!({
-                        "id": id,
-                        "type": wtype,
-                        "title": title,
-                        "settings": settings,
-                        "position": pos
-                    })
-                })
-                .collect();
-
-            Ok(json(serde_json::json!({
-                "id": id,
-                "slug": slug,
-                "name": name,
-                "widgets": widget_list
-            })))
-        }
-        None => Err(rustpress_core::error::Error::not_found("WidgetArea", area_id).into()),
-    }
+/// SEO analysis request
+#[derive(Debug, Deserialize)]
+struct SeoAnalyzeRequest {
+    title: String,
+    content: String,
+    focus_keyword: Option<String>,
+    meta_description: Option<String>,
 }
 
-/// Update widget area (reorder widgets)
-async fn update_widget_area_handler(
+/// Analyze content for SEO
+async fn analyze_seo_handler(
     user: AuthUser,
-    axum::extract::Path(area_id): axum::extract::Path<String>,
-    State(state): State<AppState>,
-    Json(payload): Json<serde_json::Value>,
-) -> HttpResult<impl axum::response::IntoResponse> {
-    Ok(json(
-        serde_json::json!({ "area_id": area_id, "updated": true }),
-    ))
-}
-
-/// Get widget
-async fn get_widget_handler(
-    PathId(id): PathId,
-    State(state): State<AppState>,
+    Json(payload): Json<SeoAnalyzeRequest>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
-
-    let widget: Option<(Uuid, String, String, serde_json::Value)> = sqlx::query_as(
-        "SELECT id, widget_type, title, settings FROM widgets WHERE id = $1 AND deleted_at IS NULL",
-    )
-    .bind(id)
-    .fetch_optional(pool)
-    .await
-    .map_err(|e| rustpress_core::error::Error::database_with_source("Failed to get widget", e))?;
+    let mut score = 0;
+    let mut suggestions = Vec::new();
 
-    match widget {
-        Some((id, wtype, title, settings)) => Ok(json(serde_json::json!({
-            "id": id, "type": wtype, "title": title, "settings": settings
-        }))),
-        None => Err(rustpress_core::error::Error::not_found("Widget", id.to_string()).into()),
+    // Title analysis
+    let title_len = payload.title.len();
+    if title_len >= 30 && title_len <= 60 {
+        score += 20;
+    } else if title_len < 30 {
+        suggestions.push("Title is too short. Aim for 30-60 characters.");
+    } else {
+        suggestions.push("Title is too long. Keep it under 60 characters.");
     }
-}
 
-/// Update widget
-async fn update_widget_handler(
-    user: AuthUser,
-    PathId(id): PathId,
-    State(state): State<AppState>,
-    Json(payload): Json<serde_json::Value>,
-) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
+    // Content length
+    let word_count = payload.content.split_whitespace().count();
+    if word_count >= 300 {
+        score += 20;
+    } else {
+        suggestions.push("Content is too short. Aim for at least 300 words.");
+    }
 
-    if let Some(settings) = payload.get("settings") {
-        sqlx::query("UPDATE widgets SET settings = $1, updated_at = NOW() WHERE id = $2")
-            .bind(settings)
-            .bind(id)
-            .execute(pool)
-            .await
-            .ok();
+    // Meta description
+    if let Some(ref desc) = payload.meta_description {
+        let desc_len = desc.len();
+        if desc_len >= 120 && desc_len <= 160 {
+            score += 20;
+        } else if desc_len < 120 {
+            suggestions.push("Meta description is too short. Aim for 120-160 characters.");
+        } else {
+            suggestions.push("Meta description is too long. Keep it under 160 characters.");
+        }
+    } else {
+        suggestions.push("Add a meta description for better SEO.");
     }
 
-    Ok(json(serde_json::json!({ "id": id, "updated": true })))
-}
+    // Focus keyword analysis
+    if let Some(ref keyword) = payload.focus_keyword {
+        if payload
+            .title
+            .to_lowercase()
+            .contains(&keyword.to_lowercase())
+        {
+            score += 15;
+        } else {
+            suggestions.push("Include your focus keyword in the title.");
+        }
 
-/// Delete widget
-async fn delete_widget_handler(
-    user: AuthUser,
-    PathId(id): PathId,
-    State(state): State<AppState>,
-) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
+        if payload
+            .content
+            .to_lowercase()
+            .contains(&keyword.to_lowercase())
+        {
+            score += 15;
+        } else {
+            suggestions.push("Include your focus keyword in the content.");
+        }
+    } else {
+        suggestions.push("Set a focus keyword for better optimization.");
+    }
 
-    sqlx::query("UPDATE widgets SET deleted_at = NOW() WHERE id = $1")
-        .bind(id)
-        .execute(pool)
-        .await
-        .map_err(|e| {
-            rustpress_core::error::Error::database_with_source("Failed to delete widget", e)
-        })?;
+    // Readability (simple check)
+    let avg_sentence_len = payload.content.split('.').count();
+    if avg_sentence_len > 0 && word_count / avg_sentence_len < 25 {
+        score += 10;
+    } else {
+        suggestions.push("Consider using shorter sentences for better readability.");
+    }
 
-    Ok(no_content())
+    Ok(json(serde_json::json!({
+        "score": score,
+        "max_score": 100,
+        "grade": if score >= 80 { "A" } else if score >= 60 { "B" } else if score >= 40 { "C" } else { "D" },
+        "suggestions": suggestions,
+        "word_count": word_count,
+        "reading_time_minutes": (word_count / 200).max(1)
+    })))
 }
 
-// =============================================================================
-// Stats/Dashboard Routes and Handlers
-// =============================================================================
-
-/// Stats/Dashboard routes
-fn stats_routes() -> Router<AppState> {
-    Router::new()
-        .route("/", get(dashboard_stats_handler))
-        .route("/dashboard", get(dashboard_stats_handler))
-        .route("/posts", get(posts_stats_handler))
-        .route("/overview", get(stats_overview_handler))
-        .route("/content", get(content_stats_handler))
-        .route("/activity", get(activity_stats_handler))
+/// Bulk analyze request
+#[derive(Debug, Deserialize)]
+struct BulkAnalyzeRequest {
+    ids: Vec<Uuid>,
+    #[serde(rename = "type")]
+    content_type: String,
 }
 
-/// Get dashboard stats
-async fn dashboard_stats_handler(
+/// Bulk analyze content for SEO
+async fn bulk_analyze_seo_handler(
     user: AuthUser,
     State(state): State<AppState>,
+    Json(payload): Json<BulkAnalyzeRequest>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
-
-    // Get counts
-    let posts: (i64,) = sqlx::query_as(
-        "SELECT COUNT(*) FROM posts WHERE post_type = 'post' AND deleted_at IS NULL",
-    )
-    .fetch_one(pool)
-    .await
-    .unwrap_or((0,));
-    let pages: (i64,) = sqlx::query_as(
-        "SELECT COUNT(*) FROM posts WHERE post_type = 'page' AND deleted_at IS NULL",
-    )
-    .fetch_one(pool)
-    .await
-    .unwrap_or((0,));
-    let comments: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM comments WHERE deleted_at IS NULL")
-        .fetch_one(pool)
-        .await
-        .unwrap_or((0,));
-    let users: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM users WHERE deleted_at IS NULL")
-        .fetch_one(pool)
-        .await
-        .unwrap_or((0,));
-    let media: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM media WHERE deleted_at IS NULL")
-        .fetch_one(pool)
-        .await
-        .unwrap_or((0,));
-
+    // In production, this would queue analysis jobs
     Ok(json(serde_json::json!({
-        "posts": posts.0,
-        "pages": pages.0,
-        "comments": comments.0,
-        "users": users.0,
-        "media": media.0,
-        "published_posts": posts.0,
-        "draft_posts": 0,
-        "pending_comments": 0
+        "queued": payload.ids.len(),
+        "message": "Analysis jobs queued"
     })))
 }
 
-/// Get posts stats
-async fn posts_stats_handler(
+/// SEO dashboard statistics
+async fn seo_dashboard_handler(
     user: AuthUser,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
     let pool = state.db().inner();
 
-    let total: (i64,) = sqlx::query_as(
-        "SELECT COUNT(*) FROM posts WHERE post_type = 'post' AND deleted_at IS NULL",
-    )
+    let post_count: (i64,) = sqlx::query_as(&format!(
+        "SELECT COUNT(*) FROM posts WHERE status = 'published' AND deleted_at IS NULL AND {}",
+        site_filter("site_id")
+    ))
     .fetch_one(pool)
     .await
     .unwrap_or((0,));
-    let published: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM posts WHERE post_type = 'post' AND status = 'published' AND deleted_at IS NULL")
-        .fetch_one(pool).await.unwrap_or((0,));
-    let draft: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM posts WHERE post_type = 'post' AND status = 'draft' AND deleted_at IS NULL")
-        .fetch_one(pool).await.unwrap_or((0,));
 
     Ok(json(serde_json::json!({
-        "total": total.0,
-        "published": published.0,
-        "draft": draft.0,
-        "scheduled": 0,
-        "pending": 0,
-        "views_total": 0,
-        "views_today": 0
+        "total_pages_indexed": post_count.0,
+        "average_seo_score": 72,
+        "pages_without_meta": 5,
+        "pages_with_issues": 12,
+        "sitemap_urls": post_count.0 + 2
     })))
 }
 
-/// Get overview stats
-async fn stats_overview_handler(
-    user: AuthUser,
-    State(state): State<AppState>,
-) -> HttpResult<impl axum::response::IntoResponse> {
-    Ok(json(serde_json::json!({
-        "total_views": 12500,
-        "views_today": 245,
-        "views_this_week": 1850,
-        "views_this_month": 7200,
-        "top_posts": [],
-        "top_pages": [],
-        "referrers": []
-    })))
-}
+use rustpress_themes::structured_data::{
+    PageStructuredData, StructuredDataAudit, StructuredDataValidator,
+};
 
-/// Get content stats
-async fn content_stats_handler(
-    user: AuthUser,
-    State(state): State<AppState>,
-) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
+/// Default and maximum number of pages checked by a structured data audit
+const STRUCTURED_DATA_AUDIT_DEFAULT: i64 = 100;
+const STRUCTURED_DATA_AUDIT_MAX: i64 = 1000;
 
-    let published: (i64,) = sqlx::query_as(
-        "SELECT COUNT(*) FROM posts WHERE status = 'published' AND deleted_at IS NULL",
-    )
-    .fetch_one(pool)
-    .await
-    .unwrap_or((0,));
-    let drafts: (i64,) =
-        sqlx::query_as("SELECT COUNT(*) FROM posts WHERE status = 'draft' AND deleted_at IS NULL")
-            .fetch_one(pool)
-            .await
-            .unwrap_or((0,));
+/// Structured data audit query
+#[derive(Debug, Deserialize)]
+struct StructuredDataAuditQuery {
+    /// Number of most recently updated posts/pages to check
+    limit: Option<i64>,
+    /// Only check this post type (`post` or `page`)
+    post_type: Option<String>,
+}
 
-    Ok(json(serde_json::json!({
-        "published": published.0,
-        "drafts": drafts.0,
-        "scheduled": 0,
-        "by_category": [],
-        "by_author": []
-    })))
+/// A page that could not be rendered for the audit
+#[derive(Debug, Serialize)]
+struct StructuredDataRenderFailure {
+    url: String,
+    post_id: String,
+    error: String,
 }
 
-/// Get activity stats
-async fn activity_stats_handler(
+/// Structured data audit response
+#[derive(Debug, Serialize)]
+struct StructuredDataAuditResponse {
+    #[serde(flatten)]
+    audit: StructuredDataAudit,
+    render_errors: Vec<StructuredDataRenderFailure>,
+}
+
+/// Render published posts and pages and validate their structured data,
+/// reporting per page and per template
+async fn structured_data_audit_handler(
     user: AuthUser,
+    Query(query): Query<StructuredDataAuditQuery>,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    Ok(json(serde_json::json!({
-        "recent_posts": [],
-        "recent_comments": [],
-        "recent_users": [],
-        "activity_log": []
-    })))
-}
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can run structured data audits",
+        ));
+    }
 
-// =============================================================================
-// Email Routes and Handlers
-// =============================================================================
+    let limit = query
+        .limit
+        .unwrap_or(STRUCTURED_DATA_AUDIT_DEFAULT)
+        .clamp(1, STRUCTURED_DATA_AUDIT_MAX);
 
-/// Email routes for SMTP configuration and testing
-fn email_routes() -> Router<AppState> {
-    Router::new()
-        .route(
-            "/config",
-            get(email_config_handler).put(email_config_update_handler),
-        )
-        .route(
-            "/settings",
-            get(email_config_handler).put(email_config_update_handler),
-        )
-        .route("/test", post(email_test_handler))
-        .route("/templates", get(email_templates_handler))
-        .route("/send", post(email_send_handler))
-}
-
-/// Email configuration response (sanitized)
-#[derive(Debug, Serialize, Deserialize)]
-struct EmailConfigResponse {
-    smtp_host: String,
-    smtp_port: u16,
-    smtp_username: Option<String>,
-    smtp_tls: bool,
-    from_email: String,
-    from_name: String,
-    site_name: String,
-    site_url: String,
-    enabled: bool,
-}
+    let rows: Vec<(Uuid, String, String)> = sqlx::query_as(&format!(
+        r#"
+        SELECT id, post_type::text, slug
+        FROM posts
+        WHERE status = 'published' AND deleted_at IS NULL AND {}
+          AND post_type::text IN ('post', 'page')
+          AND ($1::TEXT IS NULL OR post_type::text = $1)
+        ORDER BY updated_at DESC
+        LIMIT $2
+        "#,
+        site_filter("site_id")
+    ))
+    .bind(query.post_type)
+    .bind(limit)
+    .fetch_all(state.db().inner())
+    .await
+    .map_err(|e| rustpress_core::error::Error::database_with_source("Failed to load posts", e))?;
 
-/// Email configuration update request
-#[derive(Debug, Deserialize)]
-struct EmailConfigUpdate {
-    smtp_host: Option<String>,
-    smtp_port: Option<u16>,
-    smtp_username: Option<String>,
-    smtp_password: Option<String>,
-    smtp_tls: Option<bool>,
-    from_email: Option<String>,
-    from_name: Option<String>,
-    site_name: Option<String>,
-    site_url: Option<String>,
-    enabled: Option<bool>,
-}
+    let renderer = state.renderer();
+    let validator = StructuredDataValidator::new();
+    let mut audit = StructuredDataAudit::new();
+    let mut render_errors = Vec::new();
 
-/// Get email configuration
-async fn email_config_handler(
-    user: AuthUser,
-    State(state): State<AppState>,
-) -> HttpResult<impl axum::response::IntoResponse> {
-    // Only admins can view email config
-    if user.claims.role.as_deref() != Some("administrator") {
-        return Err(rustpress_core::error::Error::authorization(
-            "view email configuration",
-            "administrator",
-        )
-        .into());
-    }
+    for (id, post_type, slug) in rows {
+        let (url, rendered) = if post_type == "page" {
+            (
+                format!("/page/{}", slug),
+                renderer.render_page(&slug, None).await,
+            )
+        } else {
+            (
+                format!("/post/{}", slug),
+                renderer.render_post(&slug, None).await,
+            )
+        };
 
-    let pool = state.db().inner();
+        let page = match rendered {
+            Ok(page) => page,
+            Err(e) => {
+                render_errors.push(StructuredDataRenderFailure {
+                    url,
+                    post_id: id.to_string(),
+                    error: e.to_string(),
+                });
+                continue;
+            }
+        };
 
-    // Get email settings from options table
-    let settings: Option<(serde_json::Value,)> =
-        sqlx::query_as("SELECT option_value FROM options WHERE option_name = 'email_settings'")
-            .fetch_optional(pool)
+        let template = renderer
+            .template_for(&post_type, &slug)
             .await
-            .unwrap_or(None);
+            .ok()
+            .flatten()
+            .unwrap_or_else(|| "unknown".to_string());
 
-    let config = if let Some((value,)) = settings {
-        serde_json::from_value::<EmailConfigResponse>(value).unwrap_or_else(|_| {
-            EmailConfigResponse {
-                smtp_host: "localhost".to_string(),
-                smtp_port: 587,
-                smtp_username: None,
-                smtp_tls: true,
-                from_email: "noreply@localhost".to_string(),
-                from_name: "RustPress".to_string(),
-                site_name: "RustPress".to_string(),
-                site_url: "http://localhost".to_string(),
-                enabled: false,
-            }
-        })
-    } else {
-        EmailConfigResponse {
-            smtp_host: "localhost".to_string(),
-            smtp_port: 587,
-            smtp_username: None,
-            smtp_tls: true,
-            from_email: "noreply@localhost".to_string(),
-            from_name: "RustPress".to_string(),
-            site_name: "RustPress".to_string(),
-            site_url: "http://localhost".to_string(),
-            enabled: false,
-        }
-    };
+        audit.add_page(PageStructuredData {
+            url,
+            template,
+            post_id: Some(id.to_string()),
+            report: validator.validate(&page.html),
+        });
+    }
 
-    Ok(json(config))
+    if !render_errors.is_empty() {
+        audit.passed = false;
+    }
+
+    Ok(json(StructuredDataAuditResponse {
+        audit,
+        render_errors,
+    }))
 }
 
-/// Update email configuration
-async fn email_config_update_handler(
-    user: AuthUser,
+/// Get SEO metadata for specific content
+async fn get_content_seo_handler(
+    axum::extract::Path((content_type, id)): axum::extract::Path<(String, Uuid)>,
     State(state): State<AppState>,
-    Json(payload): Json<EmailConfigUpdate>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    // Only admins can update email config
-    if user.claims.role.as_deref() != Some("administrator") {
-        return Err(rustpress_core::error::Error::authorization(
-            "update email configuration",
-            "administrator",
-        )
-        .into());
-    }
-
     let pool = state.db().inner();
 
-    // Get current settings
-    let current: Option<(serde_json::Value,)> =
-        sqlx::query_as("SELECT option_value FROM options WHERE option_name = 'email_settings'")
+    // Get SEO metadata from post_meta or page_meta tables
+    #[allow(clippy::type_complexity)]
+    let seo: Option<(
+        Option<String>,
+        Option<String>,
+        Option<String>,
+        Option<String>,
+        Option<String>,
+    )> = match content_type.as_str() {
+        "post" | "posts" => {
+            sqlx::query_as(
+                r#"
+                SELECT
+                    (SELECT value FROM post_meta WHERE post_id = $1 AND key = 'seo_title') as seo_title,
+                    (SELECT value FROM post_meta WHERE post_id = $1 AND key = 'seo_description') as seo_description,
+                    (SELECT value FROM post_meta WHERE post_id = $1 AND key = 'focus_keyword') as focus_keyword,
+                    (SELECT value FROM post_meta WHERE post_id = $1 AND key = 'seo_noindex') as noindex,
+                    (SELECT value FROM post_meta WHERE post_id = $1 AND key = 'seo_nofollow') as nofollow
+                "#
+            )
+            .bind(id)
             .fetch_optional(pool)
             .await
-            .unwrap_or(None);
-
-    // Build updated config (merge with existing)
-    let mut config = if let Some((value,)) = current {
-        value
-    } else {
-        serde_json::json!({
-            "smtp_host": "localhost",
-            "smtp_port": 587,
-            "smtp_tls": true,
-            "from_email": "noreply@localhost",
-            "from_name": "RustPress",
-            "site_name": "RustPress",
-            "site_url": "http://localhost",
-            "enabled": false
-        })
+            .ok()
+            .flatten()
+        }
+        _ => None
     };
 
-    // Update fields if provided
-    if let Some(smtp_host) = payload.smtp_host {
-        config["smtp_host"] = serde_json::json!(smtp_host);
-    }
-    if let Some(smtp_port) = payload.smtp_port {
-        config["smtp_port"] = serde_json::json!(smtp_port);
-    }
-    if let Some(smtp_username) = payload.smtp_username {
-        config["smtp_username"] = serde_json::json!(smtp_username);
-    }
-    if let Some(smtp_password) = payload.smtp_password {
-        config["smtp_password"] = serde_json::json!(smtp_password);
-    }
-    if let Some(smtp_tls) = payload.smtp_tls {
-        config["smtp_tls"] = serde_json::json!(smtp_tls);
-    }
-    if let Some(from_email) = payload.from_email {
-        config["from_email"] = serde_json::json!(from_email);
-    }
-    if let Some(from_name) = payload.from_name {
-        config["from_name"] = serde_json::json!(from_name);
-    }
-    if let Some(site_name) = payload.site_name {
-        config["site_name"] = serde_json::json!(site_name);
-    }
-    if let Some(site_url) = payload.site_url {
-        config["site_url"] = serde_json::json!(site_url);
-    }
-    if let Some(enabled) = payload.enabled {
-        config["enabled"] = serde_json::json!(enabled);
-    }
-
-    // Save to database (upsert)
-    sqlx::query(
-        r#"
-        INSERT INTO options (option_name, option_value, autoload)
-        VALUES ('email_settings', $1, true)
-        ON CONFLICT (option_name) DO UPDATE SET option_value = $1
-        "#,
-    )
-    .bind(&config)
-    .execute(pool)
-    .await
-    .map_err(|e| {
-        rustpress_core::error::Error::database_with_source("Failed to save email settings", e)
-    })?;
+    let (seo_title, seo_description, focus_keyword, noindex, nofollow) =
+        seo.unwrap_or((None, None, None, None, None));
 
-    // Configure the email service with new settings if it exists in AppState
-    // Note: This would require adding EmailService to AppState
+    // Versioned like the theme's `og_image_url`, from the post's serialized
+    // update time
+    let updated_at: Option<chrono::DateTime<chrono::Utc>> = match content_type.as_str() {
+        "post" | "posts" | "page" | "pages" => sqlx::query_scalar(&format!(
+            "SELECT updated_at FROM posts WHERE id = $1 AND deleted_at IS NULL AND {}",
+            site_filter("site_id")
+        ))
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .ok()
+        .flatten(),
+        _ => None,
+    };
+    let site_url = state.renderer().site_url().await;
+    let og_image = updated_at.map(|at| {
+        let path = crate::services::og_image_path(
+            id,
+            &at.to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true),
+        );
+        format!("{}{}", site_url.trim_end_matches('/'), path)
+    });
+    let flag =
+        |value: Option<String>| matches!(value.as_deref(), Some("1" | "true" | "yes" | "on"));
 
     Ok(json(serde_json::json!({
-        "success": true,
-        "message": "Email configuration updated"
+        "id": id,
+        "type": content_type,
+        "seo_title": seo_title,
+        "meta_description": seo_description,
+        "focus_keyword": focus_keyword,
+        "noindex": flag(noindex),
+        "nofollow": flag(nofollow),
+        "og_title": seo_title,
+        "og_description": seo_description,
+        "og_image": og_image,
+        "twitter_title": seo_title,
+        "twitter_description": seo_description
     })))
 }
 
-/// Test email request
-#[derive(Debug, Deserialize)]
-struct EmailTestRequest {
-    to_email: String,
-}
-
-/// Send test email
-async fn email_test_handler(
+/// Update SEO metadata for specific content
+async fn update_content_seo_handler(
     user: AuthUser,
+    axum::extract::Path((content_type, id)): axum::extract::Path<(String, Uuid)>,
     State(state): State<AppState>,
-    Json(payload): Json<EmailTestRequest>,
+    Json(payload): Json<serde_json::Value>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    // Only admins can send test emails
-    if user.claims.role.as_deref() != Some("administrator") {
-        return Err(rustpress_core::error::Error::authorization(
-            "send test emails",
-            "administrator",
-        )
-        .into());
-    }
-
     let pool = state.db().inner();
 
-    // Get email settings
-    let settings: Option<(serde_json::Value,)> =
-        sqlx::query_as("SELECT option_value FROM options WHERE option_name = 'email_settings'")
-            .fetch_optional(pool)
+    // Update SEO metadata
+    if let Some(obj) = payload.as_object() {
+        for (key, value) in obj {
+            let meta_key = format!("seo_{}", key);
+            // Flags such as `noindex` arrive as booleans
+            let value_str = match value {
+                serde_json::Value::String(s) => s.clone(),
+                serde_json::Value::Null => String::new(),
+                other => other.to_string(),
+            };
+
+            sqlx::query(
+                r#"
+                INSERT INTO post_meta (id, post_id, key, value, created_at, updated_at)
+                VALUES (gen_random_uuid(), $1, $2, $3, NOW(), NOW())
+                ON CONFLICT (post_id, key) DO UPDATE SET value = $3, updated_at = NOW()
+                "#,
+            )
+            .bind(id)
+            .bind(&meta_key)
+            .bind(value_str)
+            .execute(pool)
             .await
-            .unwrap_or(None);
+            .ok();
+        }
+    }
 
-    let config = if let Some((value,)) = settings {
-        value
-    } else {
-        return Ok(json(serde_json::json!({
-            "success": false,
-            "error": "Email not configured. Please configure SMTP settings first."
-        })));
-    };
+    Ok(json(serde_json::json!({ "success": true })))
+}
 
-    let enabled = config
-        .get("enabled")
-        .and_then(|v| v.as_bool())
-        .unwrap_or(false);
-    if !enabled {
-        return Ok(json(serde_json::json!({
-            "success": false,
-            "error": "Email service is disabled. Enable it in settings first."
-        })));
-    }
+// =============================================================================
+// Cache Routes and Handlers
+// =============================================================================
 
-    // Create email service and send test
-    use crate::services::email_service::{EmailConfig, EmailService};
+use crate::services::{CachePolicyConfig, CachePolicyEngine, CacheWarmerConfig, PageCacheConfig};
 
-    let email_config = EmailConfig {
-        smtp_host: config
-            .get("smtp_host")
-            .and_then(|v| v.as_str())
-            .unwrap_or("localhost")
-            .to_string(),
-        smtp_port: config
-            .get("smtp_port")
-            .and_then(|v| v.as_u64())
-            .unwrap_or(587) as u16,
-        smtp_username: config
-            .get("smtp_username")
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string()),
-        smtp_password: config
-            .get("smtp_password")
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string()),
-        smtp_tls: config
-            .get("smtp_tls")
-            .and_then(|v| v.as_bool())
-            .unwrap_or(true),
-        from_email: config
-            .get("from_email")
-            .and_then(|v| v.as_str())
-            .unwrap_or("noreply@localhost")
-            .to_string(),
-        from_name: config
-            .get("from_name")
-            .and_then(|v| v.as_str())
-            .unwrap_or("RustPress")
-            .to_string(),
-        site_name: config
-            .get("site_name")
-            .and_then(|v| v.as_str())
-            .unwrap_or("RustPress")
-            .to_string(),
-        site_url: config
-            .get("site_url")
-            .and_then(|v| v.as_str())
-            .unwrap_or("http://localhost")
-            .to_string(),
-        enabled: true,
-    };
+/// Cache management routes
+fn cache_routes() -> Router<AppState> {
+    Router::new()
+        .route("/stats", get(cache_stats_handler))
+        .route("/clear", post(clear_all_cache_handler))
+        .route("/clear/:cache_type", post(clear_cache_by_type_handler))
+        .route("/clear/tag", post(clear_cache_by_tag_handler))
+        .route(
+            "/clear/:content_type/:id",
+            post(clear_content_cache_handler),
+        )
+        .route("/entries", get(list_cache_entries_handler))
+        .route(
+            "/config",
+            get(get_cache_config_handler).put(update_cache_config_handler),
+        )
+        .route("/warm", post(warm_cache_handler))
+        .route(
+            "/warmer",
+            get(get_cache_warmer_handler).put(update_cache_warmer_handler),
+        )
+        .route("/health", get(cache_health_handler))
+        .route(
+            "/policy",
+            get(get_cache_policy_handler).put(update_cache_policy_handler),
+        )
+        .route(
+            "/pages",
+            get(get_page_cache_handler).put(update_page_cache_handler),
+        )
+        .route("/pages/purge", post(purge_page_cache_handler))
+}
 
-    let service = EmailService::new();
-    if let Err(e) = service.configure(email_config).await {
-        return Ok(json(serde_json::json!({
-            "success": false,
-            "error": format!("Failed to configure email service: {}", e)
-        })));
-    }
+/// Get cache statistics
+async fn cache_stats_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let cache = state.cache();
 
-    match service.send_test(&payload.to_email).await {
-        Ok(result) => {
-            if result.success {
-                Ok(json(serde_json::json!({
-                    "success": true,
-                    "message": format!("Test email sent successfully to {}", payload.to_email),
-                    "message_id": result.message_id
-                })))
-            } else {
-                Ok(json(serde_json::json!({
-                    "success": false,
-                    "error": result.error.unwrap_or_else(|| "Unknown error".to_string())
-                })))
-            }
+    // Get cache stats
+    let stats = cache.stats().await;
+
+    Ok(json(serde_json::json!({
+        "hit_count": stats.hits,
+        "miss_count": stats.misses,
+        "hit_rate": if stats.hits + stats.misses > 0 {
+            (stats.hits as f64 / (stats.hits + stats.misses) as f64 * 100.0).round()
+        } else { 0.0 },
+        "total_entries": stats.entries,
+        "memory_usage_bytes": stats.memory_bytes,
+        "evictions": stats.evictions,
+        "by_type": {
+            "page": { "entries": stats.entries / 3, "size_bytes": stats.memory_bytes / 3 },
+            "query": { "entries": stats.entries / 3, "size_bytes": stats.memory_bytes / 3 },
+            "object": { "entries": stats.entries / 3, "size_bytes": stats.memory_bytes / 3 }
         }
-        Err(e) => Ok(json(serde_json::json!({
-            "success": false,
-            "error": format!("{}", e)
-        }))),
-    }
+    })))
 }
 
-/// List available email templates
-async fn email_templates_handler(
+/// Clear all cache
+async fn clear_all_cache_handler(
     user: AuthUser,
-    State(_state): State<AppState>,
+    State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    // Only admins can view templates
-    if user.claims.role.as_deref() != Some("administrator") {
-        return Err(rustpress_core::error::Error::authorization(
-            "view email templates",
-            "administrator",
-        )
-        .into());
-    }
+    let cache = state.cache();
+    cache.clear().await.map_err(|e| {
+        rustpress_core::error::Error::internal(format!("Cache clear failed: {}", e))
+    })?;
 
-    let templates = vec![
-        serde_json::json!({
-            "id": "password_reset",
-            "name": "Password Reset",
-            "description": "Sent when a user requests a password reset",
-            "variables": ["name", "reset_url", "expires_hours"]
-        }),
-        serde_json::json!({
-            "id": "email_verification",
-            "name": "Email Verification",
-            "description": "Sent to verify a user's email address",
-            "variables": ["name", "verify_url"]
-        }),
-        serde_json::json!({
-            "id": "welcome",
-            "name": "Welcome Email",
-            "description": "Sent when a new user registers",
-            "variables": ["name", "login_url"]
-        }),
-        serde_json::json!({
-            "id": "new_comment",
-            "name": "New Comment Notification",
-            "description": "Sent when a new comment is posted",
-            "variables": ["author_name", "post_title", "comment_content"]
-        }),
-        serde_json::json!({
-            "id": "comment_approved",
-            "name": "Comment Approved",
-            "description": "Sent when a comment is approved",
-            "variables": ["name", "post_title"]
-        }),
-        serde_json::json!({
-            "id": "post_published",
-            "name": "Post Published",
-            "description": "Sent when a post is published",
-            "variables": ["author_name", "post_title", "post_url"]
-        }),
-        serde_json::json!({
-            "id": "account_deactivated",
-            "name": "Account Deactivated",
-            "description": "Sent when an account is deactivated",
-            "variables": ["name"]
-        }),
-        serde_json::json!({
-            "id": "security_alert",
-            "name": "Security Alert",
-            "description": "Sent for security-related notifications",
-            "variables": ["name", "alert_type", "alert_details"]
-        }),
-    ];
+    Ok(json(serde_json::json!({
+        "success": true,
+        "message": "All cache cleared"
+    })))
+}
+
+/// Clear cache by type
+async fn clear_cache_by_type_handler(
+    user: AuthUser,
+    axum::extract::Path(cache_type): axum::extract::Path<String>,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let cache = state.cache();
+    let _ = cache.clear_by_prefix(&cache_type).await;
+
+    Ok(json(serde_json::json!({
+        "success": true,
+        "message": format!("Cache type '{}' cleared", cache_type)
+    })))
+}
+
+/// Clear cache by tag request
+#[derive(Debug, Deserialize)]
+struct ClearByTagRequest {
+    tag: String,
+}
+
+/// Clear cache by tag
+async fn clear_cache_by_tag_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(payload): Json<ClearByTagRequest>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let cache = state.cache();
+    let _ = cache.clear_by_tag(&payload.tag).await;
+    let _ = state.page_cache.purge_keys(&[payload.tag.clone()]).await;
+
+    Ok(json(serde_json::json!({
+        "success": true,
+        "message": format!("Cache with tag '{}' cleared", payload.tag)
+    })))
+}
+
+/// Clear cache for specific content
+async fn clear_content_cache_handler(
+    user: AuthUser,
+    axum::extract::Path((content_type, id)): axum::extract::Path<(String, Uuid)>,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let cache = state.cache();
+    let key = format!("{}:{}", content_type, id);
+    let _ = cache.delete(&key).await;
+
+    Ok(json(serde_json::json!({
+        "success": true,
+        "message": format!("Cache for {}:{} cleared", content_type, id)
+    })))
+}
+
+/// Cache entries query
+#[derive(Debug, Deserialize)]
+struct CacheEntriesQuery {
+    prefix: Option<String>,
+    page: Option<u32>,
+    per_page: Option<u32>,
+}
+
+/// List cache entries
+async fn list_cache_entries_handler(
+    user: AuthUser,
+    Query(query): Query<CacheEntriesQuery>,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let cache = state.cache();
+    let entries = cache.list_keys(query.prefix.as_deref()).await;
+
+    let page = query.page.unwrap_or(1) as usize;
+    let per_page = query.per_page.unwrap_or(50) as usize;
+    let offset = (page - 1) * per_page;
+
+    let paginated: Vec<serde_json::Value> = entries
+        .iter()
+        .skip(offset)
+        .take(per_page)
+        .map(|key| serde_json::json!({ "key": key }))
+        .collect();
+
+    Ok(json(serde_json::json!({
+        "entries": paginated,
+        "total": entries.len(),
+        "page": page,
+        "per_page": per_page
+    })))
+}
+
+/// Get cache configuration
+async fn get_cache_config_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    Ok(json(serde_json::json!({
+        "enabled": true,
+        "default_ttl_seconds": 3600,
+        "max_entries": 10000,
+        "max_memory_bytes": 104857600, // 100MB
+        "page_cache_enabled": true,
+        "query_cache_enabled": true,
+        "object_cache_enabled": true,
+        "api_cache_enabled": true
+    })))
+}
+
+/// Update cache configuration
+async fn update_cache_config_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(payload): Json<serde_json::Value>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    // In production, this would update cache settings
+    Ok(json(serde_json::json!({
+        "success": true,
+        "message": "Cache configuration updated"
+    })))
+}
+
+/// Get the edge cache policy
+async fn get_cache_policy_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can view the cache policy",
+        ));
+    }
+
+    let engine = state.cache_policy.engine().await;
+    Ok(json(engine.config().clone()))
+}
+
+/// Update the edge cache policy
+async fn update_cache_policy_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(config): Json<CachePolicyConfig>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can change the cache policy",
+        ));
+    }
+
+    let engine = CachePolicyEngine::new(config)?;
+    engine.config().save(state.db().inner()).await?;
+    let config = engine.config().clone();
+    state.cache_policy.set_engine(engine).await;
+
+    Ok(json(config))
+}
+
+/// Get the full-page cache settings and counters
+async fn get_page_cache_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can view the page cache",
+        ));
+    }
+
+    let config = state.page_cache.config().await;
+    Ok(json(serde_json::json!({
+        "config": config.as_ref(),
+        "stats": state.page_cache.stats(),
+    })))
+}
+
+/// Update the full-page cache settings
+async fn update_page_cache_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(config): Json<PageCacheConfig>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can change the page cache",
+        ));
+    }
+
+    state.page_cache.set_config(config.clone()).await?;
+    Ok(json(config))
+}
+
+/// Purge cached pages request
+#[derive(Debug, Deserialize)]
+struct PurgePageCacheRequest {
+    #[serde(default)]
+    keys: Vec<String>,
+    #[serde(default)]
+    all: bool,
+}
+
+/// Purge cached pages by surrogate key, or all of them
+async fn purge_page_cache_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(payload): Json<PurgePageCacheRequest>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can purge the page cache",
+        ));
+    }
+
+    if payload.all {
+        state.page_cache.purge_all().await?;
+    } else if payload.keys.is_empty() {
+        return Err(rustpress_core::error::Error::validation("No keys provided for purge").into());
+    } else {
+        state.page_cache.purge_keys(&payload.keys).await?;
+    }
+
+    Ok(json(serde_json::json!({
+        "success": true,
+        "all": payload.all,
+        "keys_purged": payload.keys.len()
+    })))
+}
+
+/// Warm up cache request
+#[derive(Debug, Deserialize)]
+struct WarmCacheRequest {
+    /// Paths to warm; the most popular pages when omitted
+    #[serde(default)]
+    urls: Vec<String>,
+}
+
+/// Queue a page cache warming run
+async fn warm_cache_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(payload): Json<WarmCacheRequest>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can warm the page cache",
+        ));
+    }
+
+    let urls = payload.urls.len();
+    let job_id = state
+        .cache_warmer
+        .enqueue(&state.job_queue, payload.urls)
+        .await?;
+
+    Ok(json(serde_json::json!({
+        "success": true,
+        "message": "Cache warm-up job queued",
+        "job_id": job_id,
+        "urls": urls,
+    })))
+}
+
+/// Get the cache warmer settings and the last run
+async fn get_cache_warmer_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can view the cache warmer",
+        ));
+    }
+
+    let config = state.cache_warmer.config().await;
+    Ok(json(serde_json::json!({
+        "config": config.as_ref(),
+        "last_run": state.cache_warmer.last_run().await,
+    })))
+}
+
+/// Update the cache warmer settings
+async fn update_cache_warmer_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(config): Json<CacheWarmerConfig>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can change the cache warmer",
+        ));
+    }
+
+    state.cache_warmer.set_config(config.clone()).await?;
+    Ok(json(config))
+}
+
+/// Get cache health
+async fn cache_health_handler(
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let cache = state.cache();
+    let stats = cache.stats().await;
+
+    let status = if stats.hits + stats.misses > 0 {
+        let hit_rate = stats.hits as f64 / (stats.hits + stats.misses) as f64;
+        if hit_rate >= 0.8 {
+            "healthy"
+        } else if hit_rate >= 0.5 {
+            "degraded"
+        } else {
+            "poor"
+        }
+    } else {
+        "unknown"
+    };
+
+    Ok(json(serde_json::json!({
+        "status": status,
+        "details": {
+            "hit_rate": if stats.hits + stats.misses > 0 {
+                (stats.hits as f64 / (stats.hits + stats.misses) as f64 * 100.0).round()
+            } else { 0.0 },
+            "memory_pressure": "low",
+            "eviction_rate": "normal"
+        }
+    })))
+}
+
+// =============================================================================
+// CDN Routes and Handlers
+// =============================================================================
+
+/// CDN management routes
+fn cdn_routes() -> Router<AppState> {
+    Router::new()
+        .route("/status", get(cdn_status_handler))
+        .route(
+            "/config",
+            get(get_cdn_config_handler).put(update_cdn_config_handler),
+        )
+        .route("/purge/all", post(cdn_purge_all_handler))
+        .route("/purge/urls", post(cdn_purge_urls_handler))
+        .route("/purge/tags", post(cdn_purge_tags_handler))
+        .route("/stats", get(cdn_stats_handler))
+        .route("/health", get(cdn_health_handler))
+}
+
+/// CDN purge URLs request
+#[derive(Debug, Deserialize)]
+struct CdnPurgeUrlsRequest {
+    urls: Vec<String>,
+}
+
+/// CDN purge tags request
+#[derive(Debug, Deserialize)]
+struct CdnPurgeTagsRequest {
+    tags: Vec<String>,
+}
+
+/// CDN configuration request
+#[derive(Debug, Deserialize)]
+struct CdnConfigRequest {
+    provider: Option<String>,
+    api_key: Option<String>,
+    zone_id: Option<String>,
+    enabled: Option<bool>,
+}
+
+/// Get CDN status
+async fn cdn_status_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    // Get CDN configuration from options
+    let pool = state.db().inner();
+    let cdn_enabled: Option<(String,)> =
+        sqlx::query_as("SELECT option_value::text FROM options WHERE option_name = 'cdn_enabled'")
+            .fetch_optional(pool)
+            .await
+            .unwrap_or(None);
+
+    let cdn_provider: Option<(String,)> =
+        sqlx::query_as("SELECT option_value::text FROM options WHERE option_name = 'cdn_provider'")
+            .fetch_optional(pool)
+            .await
+            .unwrap_or(None);
+
+    let enabled = cdn_enabled.map(|v| v.0 == "true").unwrap_or(false);
+    let provider = cdn_provider
+        .map(|v| v.0)
+        .unwrap_or_else(|| "none".to_string());
+
+    Ok(json(serde_json::json!({
+        "enabled": enabled,
+        "provider": provider,
+        "status": if enabled { "active" } else { "inactive" },
+        "features": {
+            "purge_urls": true,
+            "purge_tags": provider == "cloudflare",
+            "purge_all": true,
+            "stats": provider != "none"
+        }
+    })))
+}
+
+/// Get CDN configuration
+async fn get_cdn_config_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let pool = state.db().inner();
+
+    let options: Vec<(String, String)> = sqlx::query_as(
+        "SELECT option_name, option_value::text FROM options WHERE option_name LIKE 'cdn_%'",
+    )
+    .fetch_all(pool)
+    .await
+    .unwrap_or_default();
+
+    let mut config = serde_json::Map::new();
+    for (key, value) in options {
+        let key = key.strip_prefix("cdn_").unwrap_or(&key);
+        // Mask sensitive values
+        if key == "api_key" || key == "api_token" {
+            config.insert(key.to_string(), serde_json::json!("***"));
+        } else {
+            config.insert(key.to_string(), serde_json::json!(value));
+        }
+    }
+
+    Ok(json(serde_json::json!({
+        "config": config,
+        "available_providers": ["cloudflare", "bunnycdn", "fastly", "keycdn"]
+    })))
+}
+
+/// Update CDN configuration
+async fn update_cdn_config_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(payload): Json<CdnConfigRequest>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let pool = state.db().inner();
+
+    // Update each provided configuration option
+    if let Some(provider) = &payload.provider {
+        sqlx::query(
+            r#"
+            INSERT INTO options (option_name, option_value, autoload)
+            VALUES ('cdn_provider', $1::jsonb, true)
+            ON CONFLICT (option_name) WHERE site_id IS NULL
+            DO UPDATE SET option_value = $1::jsonb, updated_at = NOW()
+            "#,
+        )
+        .bind(serde_json::json!(provider))
+        .execute(pool)
+        .await
+        .ok();
+    }
+
+    if let Some(api_key) = &payload.api_key {
+        sqlx::query(
+            r#"
+            INSERT INTO options (option_name, option_value, autoload)
+            VALUES ('cdn_api_key', $1::jsonb, false)
+            ON CONFLICT (option_name) WHERE site_id IS NULL
+            DO UPDATE SET option_value = $1::jsonb, updated_at = NOW()
+            "#,
+        )
+        .bind(serde_json::json!(api_key))
+        .execute(pool)
+        .await
+        .ok();
+    }
+
+    if let Some(zone_id) = &payload.zone_id {
+        sqlx::query(
+            r#"
+            INSERT INTO options (option_name, option_value, autoload)
+            VALUES ('cdn_zone_id', $1::jsonb, false)
+            ON CONFLICT (option_name) WHERE site_id IS NULL
+            DO UPDATE SET option_value = $1::jsonb, updated_at = NOW()
+            "#,
+        )
+        .bind(serde_json::json!(zone_id))
+        .execute(pool)
+        .await
+        .ok();
+    }
+
+    if let Some(enabled) = payload.enabled {
+        sqlx::query(
+            r#"
+            INSERT INTO options (option_name, option_value, autoload)
+            VALUES ('cdn_enabled', $1::jsonb, true)
+            ON CONFLICT (option_name) WHERE site_id IS NULL
+            DO UPDATE SET option_value = $1::jsonb, updated_at = NOW()
+            "#,
+        )
+        .bind(serde_json::json!(enabled))
+        .execute(pool)
+        .await
+        .ok();
+    }
+
+    Ok(json(serde_json::json!({
+        "success": true,
+        "message": "CDN configuration updated"
+    })))
+}
+
+/// Purge all CDN cache
+async fn cdn_purge_all_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    // Also clear local cache
+    let cache = state.cache();
+    let _ = cache.clear().await;
+
+    // In production, would call CDN provider API
+    // For now, just return success
+    Ok(json(serde_json::json!({
+        "success": true,
+        "message": "CDN cache purge initiated",
+        "purged": {
+            "local_cache": true,
+            "cdn_cache": true
+        }
+    })))
+}
+
+/// Purge specific URLs from CDN
+async fn cdn_purge_urls_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(payload): Json<CdnPurgeUrlsRequest>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let urls = &payload.urls;
+
+    if urls.is_empty() {
+        return Err(rustpress_core::error::Error::validation("No URLs provided for purge").into());
+    }
+
+    if urls.len() > 30 {
+        return Err(
+            rustpress_core::error::Error::validation("Maximum 30 URLs per purge request").into(),
+        );
+    }
+
+    // Clear related local cache entries
+    let cache = state.cache();
+    for url in urls {
+        // Create a cache key from URL
+        let key = format!(
+            "page:{}",
+            url.trim_start_matches("http://")
+                .trim_start_matches("https://")
+        );
+        let _ = cache.delete(&key).await;
+    }
+
+    // In production, would call CDN provider API
+    Ok(json(serde_json::json!({
+        "success": true,
+        "message": format!("{} URLs purged", urls.len()),
+        "urls_purged": urls.len()
+    })))
+}
+
+/// Purge by cache tags
+async fn cdn_purge_tags_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(payload): Json<CdnPurgeTagsRequest>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let tags = &payload.tags;
+
+    if tags.is_empty() {
+        return Err(rustpress_core::error::Error::validation("No tags provided for purge").into());
+    }
+
+    // Clear local cache by tags
+    let cache = state.cache();
+    let mut total_purged = 0u64;
+    for tag in tags {
+        if let Ok(count) = cache.clear_by_tag(tag).await {
+            total_purged += count;
+        }
+    }
+
+    // Pages are cached under the same surrogate keys
+    let _ = state.page_cache.purge_keys(tags).await;
+
+    // In production, would call CDN provider API for tag-based purge
+    Ok(json(serde_json::json!({
+        "success": true,
+        "message": format!("{} tags purged", tags.len()),
+        "tags_purged": tags.len(),
+        "local_entries_cleared": total_purged
+    })))
+}
+
+/// Get CDN statistics
+async fn cdn_stats_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    // In production, would fetch from CDN provider API
+    // Return placeholder stats for now
+    Ok(json(serde_json::json!({
+        "total_requests": 0,
+        "cached_requests": 0,
+        "cache_hit_ratio": 0.0,
+        "bandwidth_saved_bytes": 0,
+        "total_bandwidth_bytes": 0,
+        "threats_blocked": 0,
+        "period": {
+            "start": chrono::Utc::now() - chrono::Duration::days(7),
+            "end": chrono::Utc::now()
+        },
+        "note": "Connect a CDN provider to view real statistics"
+    })))
+}
+
+/// CDN health check
+async fn cdn_health_handler(
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let pool = state.db().inner();
+
+    let cdn_enabled: Option<(String,)> =
+        sqlx::query_as("SELECT option_value::text FROM options WHERE option_name = 'cdn_enabled'")
+            .fetch_optional(pool)
+            .await
+            .unwrap_or(None);
+
+    let enabled = cdn_enabled.map(|v| v.0 == "true").unwrap_or(false);
+
+    Ok(json(serde_json::json!({
+        "status": if enabled { "healthy" } else { "not_configured" },
+        "cdn_enabled": enabled,
+        "local_cache_healthy": true,
+        "details": {
+            "provider_reachable": enabled,
+            "ssl_valid": true,
+            "last_purge": null
+        }
+    })))
+}
+
+// =============================================================================
+// Taxonomy Routes and Handlers (Categories, Tags)
+// =============================================================================
+
+/// Taxonomy management routes
+fn taxonomy_routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/",
+            get(list_taxonomies_handler).post(register_taxonomy_handler),
+        )
+        .route(
+            "/categories",
+            get(list_categories_handler).post(create_category_handler),
+        )
+        .route(
+            "/categories/:id",
+            get(get_category_handler)
+                .put(update_category_handler)
+                .delete(delete_category_handler),
+        )
+        .route("/tags", get(list_tags_handler).post(create_tag_handler))
+        .route(
+            "/tags/:id",
+            get(get_tag_handler)
+                .put(update_tag_handler)
+                .delete(delete_tag_handler),
+        )
+        .route(
+            "/:taxonomy",
+            get(get_taxonomy_handler)
+                .put(update_taxonomy_handler)
+                .delete(delete_taxonomy_handler),
+        )
+        .route(
+            "/:taxonomy/terms",
+            get(list_terms_handler).post(create_term_handler),
+        )
+}
+
+/// List categories
+async fn list_categories_handler(
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let pool = state.db().inner();
+
+    let categories: Vec<(Uuid, String, String, Option<String>, Option<Uuid>, i32)> = sqlx::query_as(
+        r#"
+        SELECT id, name, slug, description, parent_id,
+               (SELECT COUNT(*) FROM post_categories WHERE category_id = categories.id)::int as post_count
+        FROM categories
+        ORDER BY name
+        "#
+    )
+    .fetch_all(pool)
+    .await
+    .unwrap_or_default();
+
+    let cat_list: Vec<serde_json::Value> = categories
+        .iter()
+        .map(|(id, name, slug, desc, parent, count)| {
+            serde_json::json!({
+                "id": id,
+                "name": name,
+                "slug": slug,
+                "description": desc,
+                "parent_id": parent,
+                "post_count": count
+            })
+        })
+        .collect();
+
+    Ok(json(serde_json::json!({ "categories": cat_list })))
+}
+
+/// Create category request
+#[derive(Debug, Deserialize)]
+struct CreateCategoryRequest {
+    name: String,
+    slug: Option<String>,
+    description: Option<String>,
+    parent_id: Option<Uuid>,
+}
+
+/// Create category
+async fn create_category_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(payload): Json<CreateCategoryRequest>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let pool = state.db().inner();
+    let id = Uuid::now_v7();
+    let slug = payload
+        .slug
+        .unwrap_or_else(|| slugify::slugify(&payload.name, "-", "", None));
+    let now = chrono::Utc::now();
+
+    sqlx::query(
+        r#"
+        INSERT INTO categories (id, name, slug, description, parent_id, created_at, updated_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $6)
+        "#,
+    )
+    .bind(id)
+    .bind(&payload.name)
+    .bind(&slug)
+    .bind(&payload.description)
+    .bind(payload.parent_id)
+    .bind(now)
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        rustpress_core::error::Error::database_with_source("Failed to create category", e)
+    })?;
+
+    Ok(created(serde_json::json!({
+        "id": id,
+        "name": payload.name,
+        "slug": slug
+    })))
+}
+
+/// Get category
+async fn get_category_handler(
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let pool = state.db().inner();
+
+    let category: Option<(Uuid, String, String, Option<String>, Option<Uuid>)> = sqlx::query_as(
+        "SELECT id, name, slug, description, parent_id FROM categories WHERE id = $1",
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| rustpress_core::error::Error::database_with_source("Failed to get category", e))?;
+
+    match category {
+        Some((id, name, slug, desc, parent)) => Ok(json(serde_json::json!({
+            "id": id, "name": name, "slug": slug, "description": desc, "parent_id": parent
+        }))),
+        None => Err(rustpress_core::error::Error::not_found("Category", id.to_string()).into()),
+    }
+}
+
+/// Update category
+async fn update_category_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+    Json(payload): Json<serde_json::Value>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let pool = state.db().inner();
+
+    if let Some(name) = payload.get("name").and_then(|v| v.as_str()) {
+        sqlx::query("UPDATE categories SET name = $1, updated_at = NOW() WHERE id = $2")
+            .bind(name)
+            .bind(id)
+            .execute(pool)
+            .await
+            .ok();
+    }
+
+    Ok(json(serde_json::json!({ "id": id, "updated": true })))
+}
+
+/// Delete category
+async fn delete_category_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let pool = state.db().inner();
+
+    sqlx::query("DELETE FROM categories WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            rustpress_core::error::Error::database_with_source("Failed to delete category", e)
+        })?;
+
+    Ok(no_content())
+}
+
+/// List tags
+async fn list_tags_handler(
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let pool = state.db().inner();
+
+    let tags: Vec<(Uuid, String, String, Option<String>, i32)> = sqlx::query_as(
+        r#"
+        SELECT id, name, slug, description,
+               (SELECT COUNT(*) FROM post_tags WHERE tag_id = tags.id)::int as post_count
+        FROM tags
+        ORDER BY name
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+    .unwrap_or_default();
+
+    let tag_list: Vec<serde_json::Value> = tags
+        .iter()
+        .map(|(id, name, slug, desc, count)| {
+            serde_json::json!({
+                "id": id,
+                "name": name,
+                "slug": slug,
+                "description": desc,
+                "post_count": count
+            })
+        })
+        .collect();
+
+    Ok(json(serde_json::json!({ "tags": tag_list })))
+}
+
+/// Create tag request
+#[derive(Debug, Deserialize)]
+struct CreateTagRequest {
+    name: String,
+    slug: Option<String>,
+    description: Option<String>,
+}
+
+/// Create tag
+async fn create_tag_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(payload): Json<CreateTagRequest>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let pool = state.db().inner();
+    let id = Uuid::now_v7();
+    let slug = payload
+        .slug
+        .unwrap_or_else(|| slugify::slugify(&payload.name, "-", "", None));
+    let now = chrono::Utc::now();
+
+    sqlx::query(
+        r#"
+        INSERT INTO tags (id, name, slug, description, created_at, updated_at)
+        VALUES ($1, $2, $3, $4, $5, $5)
+        "#,
+    )
+    .bind(id)
+    .bind(&payload.name)
+    .bind(&slug)
+    .bind(&payload.description)
+    .bind(now)
+    .execute(pool)
+    .await
+    .map_err(|e| rustpress_core::error::Error::database_with_source("Failed to create tag", e))?;
+
+    Ok(created(serde_json::json!({
+        "id": id,
+        "name": payload.name,
+        "slug": slug
+    })))
+}
+
+/// Get tag
+async fn get_tag_handler(
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let pool = state.db().inner();
+
+    let tag: Option<(Uuid, String, String, Option<String>)> =
+        sqlx::query_as("SELECT id, name, slug, description FROM tags WHERE id = $1")
+            .bind(id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| {
+                rustpress_core::error::Error::database_with_source("Failed to get tag", e)
+            })?;
+
+    match tag {
+        Some((id, name, slug, desc)) => Ok(json(serde_json::json!({
+            "id": id, "name": name, "slug": slug, "description": desc
+        }))),
+        None => Err(rustpress_core::error::Error::not_found("Tag", id.to_string()).into()),
+    }
+}
+
+/// Update tag
+async fn update_tag_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+    Json(payload): Json<serde_json::Value>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let pool = state.db().inner();
+
+    if let Some(name) = payload.get("name").and_then(|v| v.as_str()) {
+        sqlx::query("UPDATE tags SET name = $1, updated_at = NOW() WHERE id = $2")
+            .bind(name)
+            .bind(id)
+            .execute(pool)
+            .await
+            .ok();
+    }
+
+    Ok(json(serde_json::json!({ "id": id, "updated": true })))
+}
+
+/// Delete tag
+async fn delete_tag_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let pool = state.db().inner();
+
+    sqlx::query("DELETE FROM tags WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            rustpress_core::error::Error::database_with_source("Failed to delete tag", e)
+        })?;
+
+    Ok(no_content())
+}
+
+// =============================================================================
+// Taxonomy Registry and Term Routes
+// =============================================================================
+
+use crate::services::taxonomy::term_keys;
+use crate::services::{ReassignInput, SplitInput, TaxonomyInput, Term, TermInput, TermQuery};
+
+/// Term routes (terms are created through their taxonomy)
+fn term_routes() -> Router<AppState> {
+    Router::new()
+        .route("/reassign", post(reassign_terms_handler))
+        .route(
+            "/:id",
+            get(get_term_handler)
+                .put(update_term_handler)
+                .delete(delete_term_handler),
+        )
+        .route(
+            "/:id/meta",
+            get(get_term_meta_handler).put(update_term_meta_handler),
+        )
+        .route("/:id/merge", post(merge_term_handler))
+        .route("/:id/split", post(split_term_handler))
+}
+
+/// Drop cached archive pages listing any of the terms
+async fn purge_term_pages(state: &AppState, terms: &[Term]) {
+    if let Err(e) = state.page_cache.purge_keys(&term_keys(terms).build()).await {
+        tracing::warn!("Failed to purge cached term archives: {}", e);
+    }
+}
+
+/// Registered taxonomies
+async fn list_taxonomies_handler(
+    _user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    Ok(json(state.taxonomies.taxonomies().await?))
+}
+
+/// Register a custom taxonomy
+async fn register_taxonomy_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(payload): Json<TaxonomyInput>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can register taxonomies",
+        ));
+    }
+    Ok(created(state.taxonomies.register(payload).await?))
+}
+
+/// A registered taxonomy
+async fn get_taxonomy_handler(
+    _user: AuthUser,
+    axum::extract::Path(slug): axum::extract::Path<String>,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    Ok(json(state.taxonomies.taxonomy(&slug).await?))
+}
+
+/// Change a taxonomy's names, archive URL or post types
+async fn update_taxonomy_handler(
+    user: AuthUser,
+    axum::extract::Path(slug): axum::extract::Path<String>,
+    State(state): State<AppState>,
+    Json(payload): Json<TaxonomyInput>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can change taxonomies",
+        ));
+    }
+    let taxonomy = state.taxonomies.update_taxonomy(&slug, payload).await?;
+    let _ = state.page_cache.purge_all().await;
+    Ok(json(taxonomy))
+}
+
+/// Delete a custom taxonomy with its terms
+async fn delete_taxonomy_handler(
+    user: AuthUser,
+    axum::extract::Path(slug): axum::extract::Path<String>,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can delete taxonomies",
+        ));
+    }
+    state.taxonomies.delete_taxonomy(&slug).await?;
+    let _ = state.page_cache.purge_all().await;
+    Ok(no_content())
+}
+
+/// Terms of a taxonomy, paginated or as a tree (`?tree=true`)
+async fn list_terms_handler(
+    _user: AuthUser,
+    axum::extract::Path(taxonomy): axum::extract::Path<String>,
+    State(state): State<AppState>,
+    Query(query): Query<TermQuery>,
+    PaginatedQuery(params): PaginatedQuery,
+) -> HttpResult<Response> {
+    if query.tree {
+        return Ok(json(state.taxonomies.tree(&taxonomy).await?).into_response());
+    }
+    let page = params.page.max(1);
+    let per_page = params.per_page.clamp(1, 100);
+    let (terms, total) = state
+        .taxonomies
+        .terms(&taxonomy, &query, page, per_page)
+        .await?;
+    Ok(paginated(terms, total, page, per_page).into_response())
+}
+
+/// Add a term to a taxonomy
+async fn create_term_handler(
+    user: AuthUser,
+    axum::extract::Path(taxonomy): axum::extract::Path<String>,
+    State(state): State<AppState>,
+    Json(payload): Json<TermInput>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_permission(&user, &state, "posts", "edit").await?;
+    Ok(created(
+        state.taxonomies.create_term(&taxonomy, payload).await?,
+    ))
+}
+
+/// A term
+async fn get_term_handler(
+    _user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    Ok(json(state.taxonomies.term(id).await?))
+}
+
+/// Rename or move a term
+async fn update_term_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+    Json(payload): Json<TermInput>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_permission(&user, &state, "posts", "edit").await?;
+    let term = state.taxonomies.update_term(id, payload).await?;
+    purge_term_pages(&state, std::slice::from_ref(&term)).await;
+    Ok(json(term))
+}
+
+/// Delete a term; its children move up to its parent
+async fn delete_term_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_permission(&user, &state, "posts", "edit").await?;
+    let term = state.taxonomies.delete_term(id).await?;
+    purge_term_pages(&state, &[term]).await;
+    Ok(no_content())
+}
+
+/// Meta of a term
+async fn get_term_meta_handler(
+    _user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    Ok(json(state.taxonomies.meta(id).await?))
+}
+
+/// Set meta keys of a term; `null` removes a key
+async fn update_term_meta_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+    Json(payload): Json<std::collections::HashMap<String, serde_json::Value>>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_permission(&user, &state, "posts", "edit").await?;
+    Ok(json(state.taxonomies.update_meta(id, payload).await?))
+}
+
+/// Merge term request
+#[derive(Debug, Deserialize)]
+struct MergeTermRequest {
+    /// Term the merged term's posts and children move to
+    into: Uuid,
+}
+
+/// Merge a term into another of the same taxonomy
+async fn merge_term_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+    Json(payload): Json<MergeTermRequest>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_permission(&user, &state, "posts", "edit").await?;
+    let source = state.taxonomies.term(id).await?;
+    let target = state.taxonomies.merge(id, payload.into).await?;
+    purge_term_pages(&state, &[source, target.clone()]).await;
+    Ok(json(target))
+}
+
+/// Split a term into new terms, moving the listed posts to them
+async fn split_term_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+    Json(payload): Json<SplitInput>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_permission(&user, &state, "posts", "edit").await?;
+    let source = state.taxonomies.term(id).await?;
+    let terms = state.taxonomies.split(id, payload).await?;
+    purge_term_pages(&state, std::slice::from_ref(&source)).await;
+    Ok(created(terms))
+}
+
+/// Add and remove terms on many posts at once
+async fn reassign_terms_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(payload): Json<ReassignInput>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_permission(&user, &state, "posts", "edit").await?;
+    let post_ids = payload.post_ids.clone();
+    let report = state.taxonomies.reassign(payload).await?;
+    let keys = post_ids
+        .iter()
+        .fold(term_keys(&report.terms), |keys, id| {
+            keys.post(&id.to_string())
+        })
+        .build();
+    if let Err(e) = state.page_cache.purge_keys(&keys).await {
+        tracing::warn!("Failed to purge cached pages: {}", e);
+    }
+    Ok(json(report))
+}
+
+// =============================================================================
+// Menu Routes and Handlers
+// =============================================================================
+
+use crate::services::{MenuInput, MenuItemsInput};
+
+/// Menu management routes
+fn menu_routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_menus_handler).post(create_menu_handler))
+        .route(
+            "/locations",
+            get(list_menu_locations_handler).put(assign_menu_location_handler),
+        )
+        .route("/locations/:location", get(location_menu_handler))
+        .route(
+            "/:id",
+            get(get_menu_handler)
+                .put(update_menu_handler)
+                .delete(delete_menu_handler),
+        )
+        .route(
+            "/:id/items",
+            get(get_menu_items_handler).put(update_menu_items_handler),
+        )
+}
+
+/// Theme whose menu locations a request is about; the active one by default
+#[derive(Debug, Deserialize)]
+struct MenuThemeQuery {
+    theme: Option<String>,
+}
+
+/// Assign a menu to a theme location, or clear it with a null `menu_id`
+#[derive(Debug, Deserialize)]
+struct MenuLocationAssignment {
+    theme: Option<String>,
+    location: String,
+    menu_id: Option<Uuid>,
+}
+
+/// Menus appear on every public page, directly or through widgets
+async fn purge_menu_pages(state: &AppState) {
+    state.widgets.invalidate();
+    if let Err(e) = state.page_cache.purge_all().await {
+        tracing::warn!("Failed to purge cached pages: {}", e);
+    }
+}
+
+/// List menus
+async fn list_menus_handler(
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let menus = state.menus.list().await?;
+    Ok(json(serde_json::json!({ "menus": menus })))
+}
+
+/// List the menu locations of a theme and the menus filling them
+async fn list_menu_locations_handler(
+    State(state): State<AppState>,
+    Query(query): Query<MenuThemeQuery>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let theme_id = state.menus.theme_id(query.theme).await?;
+    let locations = state.menus.locations(&theme_id).await?;
+    Ok(json(serde_json::json!({
+        "theme": theme_id,
+        "locations": locations
+    })))
+}
+
+/// Assign a menu to a theme location
+async fn assign_menu_location_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(payload): Json<MenuLocationAssignment>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_permission(&user, &state, "themes", "manage").await?;
+    let theme_id = state.menus.theme_id(payload.theme).await?;
+    state
+        .menus
+        .assign(&theme_id, &payload.location, payload.menu_id)
+        .await?;
+    purge_menu_pages(&state).await;
+    let locations = state.menus.locations(&theme_id).await?;
+    Ok(json(serde_json::json!({
+        "theme": theme_id,
+        "locations": locations
+    })))
+}
+
+/// Menu filling a theme location, as the requesting user sees it
+async fn location_menu_handler(
+    MaybeAuthUser(user): MaybeAuthUser,
+    axum::extract::Path(location): axum::extract::Path<String>,
+    State(state): State<AppState>,
+    Query(query): Query<MenuThemeQuery>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let theme_id = state.menus.theme_id(query.theme).await?;
+    let viewer = user.as_ref().map(|u| u.roles.as_slice());
+    let menu = state
+        .menus
+        .location_menu(&theme_id, &location, viewer)
+        .await?
+        .ok_or_else(|| rustpress_core::error::Error::not_found("Menu location", &location))?;
+    Ok(json(menu))
+}
+
+/// Create menu
+async fn create_menu_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(payload): Json<MenuInput>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_permission(&user, &state, "themes", "manage").await?;
+    let menu = state.menus.create(payload).await?;
+    purge_menu_pages(&state).await;
+    Ok(created(menu))
+}
+
+/// Get menu with its item tree
+async fn get_menu_handler(
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let menu = state.menus.menu(id).await?;
+    let version = menu.menu.version;
+    Ok(versioned(menu, version))
+}
+
+/// Update menu
+async fn update_menu_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+    IfMatch(if_match): IfMatch,
+    Json(payload): Json<MenuInput>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_permission(&user, &state, "themes", "manage").await?;
+    let menu = state.menus.update(id, payload, if_match).await?;
+    purge_menu_pages(&state).await;
+    let version = menu.version;
+    Ok(versioned(menu, version))
+}
+
+/// Delete menu with its items and location assignments
+async fn delete_menu_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_permission(&user, &state, "themes", "manage").await?;
+    state.menus.delete(id).await?;
+    purge_menu_pages(&state).await;
+    Ok(no_content())
+}
+
+/// Get the item tree of a menu
+async fn get_menu_items_handler(
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let menu = state.menus.menu(id).await?;
+    let version = menu.menu.version;
+    Ok(versioned(
+        serde_json::json!({ "menu_id": id, "items": menu.items }),
+        version,
+    ))
+}
+
+/// Replace the item tree of a menu
+async fn update_menu_items_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+    IfMatch(if_match): IfMatch,
+    Json(payload): Json<MenuItemsInput>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_permission(&user, &state, "themes", "manage").await?;
+    let menu = state.menus.save_items(id, payload, if_match).await?;
+    purge_menu_pages(&state).await;
+    let version = menu.menu.version;
+    Ok(versioned(
+        serde_json::json!({ "menu_id": id, "items": menu.items }),
+        version,
+    ))
+}
+
+// =============================================================================
+// Widget Routes and Handlers
+// =============================================================================
+
+use crate::services::{WidgetInput, WidgetPlacement, CLASSIC_WIDGETS};
+
+/// Widget management routes
+fn widget_routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/",
+            get(list_widget_types_handler).post(create_widget_handler),
+        )
+        .route("/types", get(list_widget_types_handler))
+        .route("/areas", get(list_widget_areas_handler))
+        .route(
+            "/areas/:area",
+            get(get_widget_area_handler).put(update_widget_area_handler),
+        )
+        .route("/areas/:area/rendered", get(rendered_widget_area_handler))
+        .route(
+            "/:id",
+            get(get_widget_handler)
+                .put(update_widget_handler)
+                .delete(delete_widget_handler),
+        )
+}
+
+/// Theme whose widget areas a request is about; the active one by default
+#[derive(Debug, Deserialize)]
+struct WidgetThemeQuery {
+    theme: Option<String>,
+}
+
+/// Widgets appear on every public page
+async fn purge_widget_pages(state: &AppState) {
+    if let Err(e) = state.page_cache.purge_all().await {
+        tracing::warn!("Failed to purge cached pages: {}", e);
+    }
+}
+
+/// List classic widget types; any block can also be placed as a widget
+async fn list_widget_types_handler(
+    State(_state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let types: Vec<serde_json::Value> = CLASSIC_WIDGETS
+        .iter()
+        .map(|(widget_type, name, description)| {
+            serde_json::json!({
+                "type": widget_type,
+                "name": name,
+                "description": description
+            })
+        })
+        .collect();
+    Ok(json(serde_json::json!({ "types": types, "blocks": true })))
+}
+
+/// List the widget areas of a theme with their widgets
+async fn list_widget_areas_handler(
+    State(state): State<AppState>,
+    Query(query): Query<WidgetThemeQuery>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let theme_id = state.widgets.theme_id(query.theme).await?;
+    let areas = state.widgets.areas(&theme_id).await?;
+    Ok(json(serde_json::json!({
+        "theme": theme_id,
+        "areas": areas
+    })))
+}
+
+/// Get widget area with widgets
+async fn get_widget_area_handler(
+    axum::extract::Path(area): axum::extract::Path<String>,
+    State(state): State<AppState>,
+    Query(query): Query<WidgetThemeQuery>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let theme_id = state.widgets.theme_id(query.theme).await?;
+    Ok(json(state.widgets.area(&theme_id, &area).await?))
+}
+
+/// Put widgets into an area in order
+async fn update_widget_area_handler(
+    user: AuthUser,
+    axum::extract::Path(area): axum::extract::Path<String>,
+    State(state): State<AppState>,
+    Json(payload): Json<WidgetPlacement>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_permission(&user, &state, "themes", "manage").await?;
+    let theme_id = state.widgets.theme_id(payload.theme).await?;
+    let area = state
+        .widgets
+        .place(&theme_id, &area, &payload.widgets)
+        .await?;
+    purge_widget_pages(&state).await;
+    Ok(json(area))
+}
+
+/// Widget area rendered as the theme outputs it
+async fn rendered_widget_area_handler(
+    axum::extract::Path(area): axum::extract::Path<String>,
+    State(state): State<AppState>,
+    Query(query): Query<WidgetThemeQuery>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let theme_id = state.widgets.theme_id(query.theme).await?;
+    Ok(json(state.widgets.rendered_area(&theme_id, &area).await?))
+}
+
+/// Create a widget at the end of an area
+async fn create_widget_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(payload): Json<WidgetInput>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_permission(&user, &state, "themes", "manage").await?;
+    let widget = state.widgets.create(payload).await?;
+    purge_widget_pages(&state).await;
+    Ok(created(widget))
+}
+
+/// Get widget
+async fn get_widget_handler(
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    Ok(json(state.widgets.widget(id).await?))
+}
+
+/// Update widget
+async fn update_widget_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+    Json(payload): Json<WidgetInput>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_permission(&user, &state, "themes", "manage").await?;
+    let widget = state.widgets.update(id, payload).await?;
+    purge_widget_pages(&state).await;
+    Ok(json(widget))
+}
+
+/// Delete widget
+async fn delete_widget_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_permission(&user, &state, "themes", "manage").await?;
+    state.widgets.delete(id).await?;
+    purge_widget_pages(&state).await;
+    Ok(no_content())
+}
+
+// =============================================================================
+// Stats/Dashboard Routes and Handlers
+// =============================================================================
+
+/// Stats/Dashboard routes
+fn stats_routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(dashboard_stats_handler))
+        .route("/dashboard", get(dashboard_stats_handler))
+        .route("/posts", get(posts_stats_handler))
+        .route("/overview", get(stats_overview_handler))
+        .route("/content", get(content_stats_handler))
+        .route("/content-performance", get(content_performance_handler))
+        .route("/authors", get(author_leaderboard_handler))
+        .route("/authors/:id", get(author_analytics_handler))
+        .route("/activity", get(activity_stats_handler))
+        .route(
+            "/bots",
+            get(bot_stats_handler).delete(reset_bot_stats_handler),
+        )
+        .route("/web-vitals", get(web_vitals_report_handler))
+        .route(
+            "/web-vitals/settings",
+            get(get_web_vitals_config_handler).put(update_web_vitals_config_handler),
+        )
+        .route("/web-vitals/alerts", get(web_vitals_alerts_handler))
+}
+
+/// Dashboard counts are refreshed in the background once they are a minute
+/// old, so expiry never sends every open dashboard to the database at once
+const DASHBOARD_STATS_SWR: rustpress_cache::SwrPolicy = rustpress_cache::SwrPolicy {
+    fresh_for: std::time::Duration::from_secs(60),
+    stale_for: std::time::Duration::from_secs(3600),
+};
+
+/// Get dashboard stats
+async fn dashboard_stats_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let pool = state.db().inner().clone();
+    // Counts are per site, and refreshed by a task of their own
+    let site = current_site();
+    let key = match site {
+        Some(site) => format!("dashboard:stats:{}", site),
+        None => "dashboard:stats".to_string(),
+    };
+
+    let stats = state
+        .cache
+        .get_swr(key, DASHBOARD_STATS_SWR, move || {
+            with_site(site, async move {
+                let pool = &pool;
+
+                // Get counts
+                let posts: (i64,) = sqlx::query_as(&format!(
+                "SELECT COUNT(*) FROM posts WHERE post_type = 'post' AND deleted_at IS NULL AND {}",
+                site_filter("site_id")
+            ))
+                .fetch_one(pool)
+                .await
+                .unwrap_or((0,));
+                let pages: (i64,) = sqlx::query_as(&format!(
+                "SELECT COUNT(*) FROM posts WHERE post_type = 'page' AND deleted_at IS NULL AND {}",
+                site_filter("site_id")
+            ))
+                .fetch_one(pool)
+                .await
+                .unwrap_or((0,));
+                let comments: (i64,) = sqlx::query_as(&format!(
+                    "SELECT COUNT(*) FROM comments WHERE deleted_at IS NULL AND {}",
+                    site_filter("site_id")
+                ))
+                .fetch_one(pool)
+                .await
+                .unwrap_or((0,));
+                let users: (i64,) =
+                    sqlx::query_as("SELECT COUNT(*) FROM users WHERE deleted_at IS NULL")
+                        .fetch_one(pool)
+                        .await
+                        .unwrap_or((0,));
+                let media: (i64,) = sqlx::query_as(&format!(
+                    "SELECT COUNT(*) FROM media WHERE deleted_at IS NULL AND {}",
+                    site_filter("site_id")
+                ))
+                .fetch_one(pool)
+                .await
+                .unwrap_or((0,));
+
+                Ok(serde_json::json!({
+                    "posts": posts.0,
+                    "pages": pages.0,
+                    "comments": comments.0,
+                    "users": users.0,
+                    "media": media.0,
+                    "published_posts": posts.0,
+                    "draft_posts": 0,
+                    "pending_comments": 0
+                }))
+            })
+        })
+        .await?;
+
+    Ok(json(stats))
+}
+
+/// Get posts stats
+async fn posts_stats_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let pool = state.db().inner();
+
+    let total: (i64,) = sqlx::query_as(&format!(
+        "SELECT COUNT(*) FROM posts WHERE post_type = 'post' AND deleted_at IS NULL AND {}",
+        site_filter("site_id")
+    ))
+    .fetch_one(pool)
+    .await
+    .unwrap_or((0,));
+    let published: (i64,) = sqlx::query_as(&format!(
+        "SELECT COUNT(*) FROM posts WHERE post_type = 'post' AND status = 'published' \
+         AND deleted_at IS NULL AND {}",
+        site_filter("site_id")
+    ))
+    .fetch_one(pool)
+    .await
+    .unwrap_or((0,));
+    let draft: (i64,) = sqlx::query_as(&format!(
+        "SELECT COUNT(*) FROM posts WHERE post_type = 'post' AND status = 'draft' \
+         AND deleted_at IS NULL AND {}",
+        site_filter("site_id")
+    ))
+    .fetch_one(pool)
+    .await
+    .unwrap_or((0,));
+
+    Ok(json(serde_json::json!({
+        "total": total.0,
+        "published": published.0,
+        "draft": draft.0,
+        "scheduled": 0,
+        "pending": 0,
+        "views_total": 0,
+        "views_today": 0
+    })))
+}
+
+/// Get overview stats
+async fn stats_overview_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    Ok(json(serde_json::json!({
+        "total_views": 12500,
+        "views_today": 245,
+        "views_this_week": 1850,
+        "views_this_month": 7200,
+        "top_posts": [],
+        "top_pages": [],
+        "referrers": []
+    })))
+}
+
+/// Get content stats
+async fn content_stats_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let pool = state.db().inner();
+
+    let published: (i64,) = sqlx::query_as(&format!(
+        "SELECT COUNT(*) FROM posts WHERE status = 'published' AND deleted_at IS NULL AND {}",
+        site_filter("site_id")
+    ))
+    .fetch_one(pool)
+    .await
+    .unwrap_or((0,));
+    let drafts: (i64,) = sqlx::query_as(&format!(
+        "SELECT COUNT(*) FROM posts WHERE status = 'draft' AND deleted_at IS NULL AND {}",
+        site_filter("site_id")
+    ))
+    .fetch_one(pool)
+    .await
+    .unwrap_or((0,));
+
+    Ok(json(serde_json::json!({
+        "published": published.0,
+        "drafts": drafts.0,
+        "scheduled": 0,
+        "by_category": [],
+        "by_author": []
+    })))
+}
+
+/// Published posts and pages joined with their analytics, for the
+/// editors' top content screen
+async fn content_performance_handler(
+    user: AuthUser,
+    Query(query): Query<crate::services::ContentPerformanceQuery>,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() && !user.has_role("editor") {
+        return Err(HttpError::forbidden(
+            "Only editors can view content performance",
+        ));
+    }
+
+    let service = crate::services::ContentPerformanceService::new(state.db().inner().clone());
+    Ok(json(service.report(&query).await?))
+}
+
+/// Analytics scope of the caller; readers without an authoring role see
+/// no author analytics
+fn author_analytics_scope(user: &AuthUser) -> HttpResult<crate::services::AnalyticsScope> {
+    crate::services::AnalyticsScope::for_user(user.id, &user.roles)
+        .ok_or_else(|| HttpError::forbidden("Only authors and editors can view author analytics"))
+}
+
+/// Authors ranked by views, engagement or publishing cadence; authors only
+/// see their own row
+async fn author_leaderboard_handler(
+    user: AuthUser,
+    Query(query): Query<crate::services::LeaderboardQuery>,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let scope = author_analytics_scope(&user)?;
+
+    let service = crate::services::AuthorAnalyticsService::new(state.db().inner().clone());
+    Ok(json(service.leaderboard(&query, scope).await?))
+}
+
+/// Rolled up analytics, top posts and daily views of one author
+async fn author_analytics_handler(
+    user: AuthUser,
+    PathId(author_id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let scope = author_analytics_scope(&user)?;
+    if !scope.allows(author_id) {
+        return Err(HttpError::forbidden(
+            "You can only view your own author analytics",
+        ));
+    }
+
+    let service = crate::services::AuthorAnalyticsService::new(state.db().inner().clone());
+    match service.author(author_id).await? {
+        Some(analytics) => Ok(json(analytics)),
+        None => Err(HttpError::not_found("Author has no published content")),
+    }
+}
+
+/// Get activity stats
+async fn activity_stats_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    Ok(json(serde_json::json!({
+        "recent_posts": [],
+        "recent_comments": [],
+        "recent_users": [],
+        "activity_log": []
+    })))
+}
+
+/// Get bot traffic stats
+async fn bot_stats_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can view bot traffic stats",
+        ));
+    }
+
+    Ok(json(state.bot_detection.stats()))
+}
+
+/// Reset bot traffic stats
+async fn reset_bot_stats_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can reset bot traffic stats",
+        ));
+    }
+
+    state.bot_detection.reset_stats();
+    Ok(json(serde_json::json!({ "reset": true })))
+}
+
+/// Core Web Vitals field data per device, template, page and day
+async fn web_vitals_report_handler(
+    user: AuthUser,
+    Query(query): Query<crate::services::WebVitalsQuery>,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() && !user.has_role("editor") {
+        return Err(HttpError::forbidden(
+            "Only editors can view Core Web Vitals",
+        ));
+    }
+
+    Ok(json(state.web_vitals.report(&query).await?))
+}
+
+/// Performance budget alerts, newest first
+async fn web_vitals_alerts_handler(
+    user: AuthUser,
+    Query(query): Query<crate::services::VitalsAlertQuery>,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() && !user.has_role("editor") {
+        return Err(HttpError::forbidden(
+            "Only editors can view performance budget alerts",
+        ));
+    }
+
+    Ok(json(state.web_vitals.alerts(&query).await?))
+}
+
+/// Get the Core Web Vitals collection and budget settings
+async fn get_web_vitals_config_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can view Core Web Vitals settings",
+        ));
+    }
+
+    Ok(json(state.web_vitals.config().await.as_ref().clone()))
+}
+
+/// Update the Core Web Vitals collection and budget settings
+async fn update_web_vitals_config_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(config): Json<crate::services::WebVitalsConfig>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can change Core Web Vitals settings",
+        ));
+    }
+
+    Ok(json(state.web_vitals.update_config(config).await?))
+}
+
+// =============================================================================
+// Anti-Abuse Challenge Routes and Handlers
+// =============================================================================
+
+use crate::services::abuse_challenge::{AbuseChallengeConfig, ChallengeFailure};
+
+/// Anti-abuse challenge routes
+fn challenge_routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/",
+            get(get_challenge_config_handler).put(update_challenge_config_handler),
+        )
+        .route("/:endpoint", get(issue_challenge_handler))
+}
+
+/// Issue a challenge for a public form
+async fn issue_challenge_handler(
+    State(state): State<AppState>,
+    axum::extract::Path(endpoint): axum::extract::Path<String>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let challenge = state.abuse_challenges.issue(&endpoint).await?;
+    Ok(json(challenge))
+}
+
+/// Get the challenge configuration
+async fn get_challenge_config_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can view challenge settings",
+        ));
+    }
+
+    let config = state.abuse_challenges.config().await;
+    Ok(json(config.as_ref().clone()))
+}
+
+/// Update the challenge configuration
+async fn update_challenge_config_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(config): Json<AbuseChallengeConfig>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can change challenge settings",
+        ));
+    }
+
+    config.validate()?;
+    config.save(state.db().inner()).await?;
+    state.abuse_challenges.set_config(config.clone()).await;
+
+    Ok(json(config))
+}
+
+/// Check a public submission against its endpoint's challenges, then decode it
+async fn challenged_body<T: serde::de::DeserializeOwned>(
+    state: &AppState,
+    endpoint: &str,
+    body: serde_json::Value,
+) -> HttpResult<T> {
+    state
+        .abuse_challenges
+        .verify(endpoint, &body)
+        .await
+        .map_err(challenge_error)?;
+
+    serde_json::from_value(body)
+        .map_err(|e| HttpError::unprocessable_entity(format!("Invalid request body: {}", e)))
+}
+
+fn challenge_error(failure: ChallengeFailure) -> HttpError {
+    let error = HttpError::new(
+        axum::http::StatusCode::FORBIDDEN,
+        "CHALLENGE_FAILED",
+        failure.to_string(),
+    );
+    // Legitimate clients need the reason to recover; honeypot hits get none
+    if failure == ChallengeFailure::HoneypotTriggered {
+        return error;
+    }
+    error.with_details(std::collections::HashMap::from([(
+        "reason".to_string(),
+        failure.as_str().to_string(),
+    )]))
+}
+
+// =============================================================================
+// CAPTCHA Routes and Handlers
+// =============================================================================
+
+use crate::services::CaptchaConfig;
+
+/// CAPTCHA routes
+fn captcha_routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/",
+            get(get_captcha_config_handler).put(update_captcha_config_handler),
+        )
+        .route("/:endpoint", get(captcha_widget_handler))
+}
+
+/// Widget settings for a public form
+async fn captcha_widget_handler(
+    State(state): State<AppState>,
+    axum::extract::Path(endpoint): axum::extract::Path<String>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    Ok(json(state.captcha.widget(&endpoint).await))
+}
+
+/// Get the CAPTCHA configuration (secret key masked)
+async fn get_captcha_config_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can view CAPTCHA settings",
+        ));
+    }
+
+    Ok(json(state.captcha.config().await.masked()))
+}
+
+/// Update the CAPTCHA configuration
+async fn update_captcha_config_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(mut config): Json<CaptchaConfig>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can change CAPTCHA settings",
+        ));
+    }
+
+    let current = state.captcha.config().await;
+    config.merge_secret(&current);
+    config.validate()?;
+    config.save(state.db().inner()).await?;
+    state.captcha.set_config(config.clone()).await;
+
+    Ok(json(config.masked()))
+}
+
+// =============================================================================
+// Content Filter Routes and Handlers
+// =============================================================================
+
+use crate::services::ContentFiltersConfig;
+
+/// Content filter routes
+fn content_filter_routes() -> Router<AppState> {
+    Router::new().route(
+        "/",
+        get(get_content_filters_handler).put(update_content_filters_handler),
+    )
+}
+
+/// Get the content filter configuration
+async fn get_content_filters_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can view content filter settings",
+        ));
+    }
+
+    Ok(json(state.content_filters.config().await.as_ref().clone()))
+}
+
+/// Update the content filter configuration
+async fn update_content_filters_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(config): Json<ContentFiltersConfig>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can change content filter settings",
+        ));
+    }
+
+    config.validate()?;
+    config.save(state.db().inner()).await?;
+    state.content_filters.set_config(config.clone());
+
+    Ok(json(config))
+}
+
+// =============================================================================
+// Maintenance Routes and Handlers
+// =============================================================================
+
+use crate::services::ReadOnlyUpdate;
+
+/// Maintenance mode routes
+fn maintenance_routes() -> Router<AppState> {
+    Router::new().route(
+        "/read-only",
+        get(get_read_only_handler).put(update_read_only_handler),
+    )
+}
+
+/// Get the read-only mode status
+async fn get_read_only_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can view maintenance settings",
+        ));
+    }
+
+    Ok(json(state.read_only.status()))
+}
+
+/// Enter or leave read-only mode
+async fn update_read_only_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(update): Json<ReadOnlyUpdate>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can change maintenance settings",
+        ));
+    }
+
+    Ok(json(state.read_only.set(update, Some(user.id)).await))
+}
+
+// =============================================================================
+// Job Routes and Handlers
+// =============================================================================
+
+use rustpress_jobs::sla::DEFAULT_SLA_WINDOW_SECS;
+use rustpress_jobs::{DeadLetterFilter, JobSla};
+
+/// Background job routes
+fn job_routes() -> Router<AppState> {
+    Router::new()
+        .route("/dead-letters", get(list_dead_letters_handler))
+        .route(
+            "/dead-letters/:id",
+            get(get_dead_letter_handler).delete(discard_dead_letter_handler),
+        )
+        .route(
+            "/dead-letters/:id/requeue",
+            post(requeue_dead_letter_handler),
+        )
+        .route("/health", get(job_health_handler))
+        .route("/executions", get(list_job_executions_handler))
+        .route("/sagas", get(list_sagas_handler))
+        .route("/sagas/:id", get(get_saga_handler))
+        .route("/slas", get(list_job_slas_handler))
+        .route(
+            "/slas/:job_type",
+            get(get_job_sla_handler)
+                .put(save_job_sla_handler)
+                .delete(delete_job_sla_handler),
+        )
+}
+
+/// Only administrators may inspect or act on failed jobs
+fn require_jobs_admin(user: &AuthUser) -> HttpResult<()> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can manage failed jobs",
+        ));
+    }
+    Ok(())
+}
+
+/// Dead-letter list filters
+#[derive(Debug, serde::Deserialize)]
+struct DeadLetterQuery {
+    queue: Option<String>,
+    job_type: Option<String>,
+    page: Option<u32>,
+    per_page: Option<u32>,
+}
+
+/// List jobs that ran out of attempts, most recent failures first
+async fn list_dead_letters_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Query(query): Query<DeadLetterQuery>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_jobs_admin(&user)?;
+
+    let page = query.page.unwrap_or(1).max(1);
+    let per_page = query.per_page.unwrap_or(20).clamp(1, 100);
+    let filter = DeadLetterFilter {
+        queue: query.queue,
+        job_type: query.job_type,
+    };
+    let dead_letters = state.job_queue.dead_letters();
+    let total = dead_letters.count(&filter).await?;
+    let letters = dead_letters
+        .list(&filter, per_page as i64, ((page - 1) * per_page) as i64)
+        .await?;
+
+    Ok(paginated(letters, total, page, per_page))
+}
+
+/// Get a failed job with its last error and payload
+async fn get_dead_letter_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_jobs_admin(&user)?;
+
+    match state.job_queue.dead_letters().get(id).await? {
+        Some(letter) => Ok(json(letter)),
+        None => Err(HttpError::not_found("Dead letter not found")),
+    }
+}
+
+/// Put a failed job back on its queue with fresh attempts
+async fn requeue_dead_letter_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_jobs_admin(&user)?;
+
+    if !state.job_queue.requeue_dead_letter(id).await? {
+        return Err(HttpError::not_found("Dead letter not found"));
+    }
+    Ok(json(serde_json::json!({ "id": id, "requeued": true })))
+}
+
+/// Drop a failed job for good
+async fn discard_dead_letter_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_jobs_admin(&user)?;
+
+    if !state.job_queue.dead_letters().discard(id).await? {
+        return Err(HttpError::not_found("Dead letter not found"));
+    }
+    Ok(no_content())
+}
+
+/// Saga list filters
+#[derive(Debug, serde::Deserialize)]
+struct SagaListQuery {
+    status: Option<rustpress_jobs::SagaStatus>,
+    limit: Option<u32>,
+}
+
+/// List sagas, most recent first, with the steps of each
+async fn list_sagas_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Query(query): Query<SagaListQuery>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_jobs_admin(&user)?;
+
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let sagas = state.sagas.list_summaries(query.status, limit).await?;
+    Ok(json(serde_json::json!({
+        "sagas": sagas,
+        "saga_types": state.sagas.saga_types(),
+    })))
+}
+
+/// Get a saga with its progress, context and steps
+async fn get_saga_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_jobs_admin(&user)?;
+
+    Ok(json(state.sagas.inspect(id).await?))
+}
+
+/// Window of job history to summarize
+#[derive(Debug, serde::Deserialize)]
+struct JobHealthQuery {
+    window_secs: Option<u64>,
+}
+
+/// Execution summary per job type over a recent window, judged against
+/// the job SLAs
+async fn job_health_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Query(query): Query<JobHealthQuery>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_jobs_admin(&user)?;
+
+    let window_secs = query
+        .window_secs
+        .unwrap_or(DEFAULT_SLA_WINDOW_SECS)
+        .clamp(60, 30 * 86_400);
+    let health = state.job_queue.sla_monitor().health(window_secs).await?;
+    let dead_letters = state
+        .job_queue
+        .dead_letters()
+        .count(&DeadLetterFilter::default())
+        .await?;
+
+    Ok(json(serde_json::json!({
+        "health": health,
+        "dead_letters": dead_letters,
+    })))
+}
+
+/// Job execution list filters
+#[derive(Debug, serde::Deserialize)]
+struct JobExecutionQuery {
+    job_type: Option<String>,
+    limit: Option<i64>,
+}
+
+/// Most recent job attempts, newest first
+async fn list_job_executions_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Query(query): Query<JobExecutionQuery>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_jobs_admin(&user)?;
+
+    let limit = query.limit.unwrap_or(50).clamp(1, 500);
+    let executions = state
+        .job_queue
+        .sla_monitor()
+        .audit()
+        .recent(query.job_type.as_deref(), limit)
+        .await?;
+
+    Ok(json(executions))
+}
+
+/// List the SLAs of every job type that has one
+async fn list_job_slas_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_jobs_admin(&user)?;
+
+    Ok(json(state.job_queue.sla_monitor().slas().list().await?))
+}
+
+/// Get the SLA of a job type
+async fn get_job_sla_handler(
+    user: AuthUser,
+    axum::extract::Path(job_type): axum::extract::Path<String>,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_jobs_admin(&user)?;
+
+    match state.job_queue.sla_monitor().slas().get(&job_type).await? {
+        Some(sla) => Ok(json(sla)),
+        None => Err(HttpError::not_found("Job SLA not found")),
+    }
+}
+
+/// Limits of a job SLA; unset limits are not checked
+#[derive(Debug, serde::Deserialize)]
+struct SaveJobSlaRequest {
+    max_duration_secs: Option<i64>,
+    max_queue_latency_secs: Option<i64>,
+    max_failure_rate: Option<f64>,
+    min_executions: Option<i32>,
+    enabled: Option<bool>,
+}
+
+/// Create or replace the SLA of a job type
+async fn save_job_sla_handler(
+    user: AuthUser,
+    axum::extract::Path(job_type): axum::extract::Path<String>,
+    State(state): State<AppState>,
+    Json(payload): Json<SaveJobSlaRequest>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_jobs_admin(&user)?;
+
+    let mut sla = JobSla::new(job_type);
+    sla.max_duration_secs = payload.max_duration_secs;
+    sla.max_queue_latency_secs = payload.max_queue_latency_secs;
+    sla.max_failure_rate = payload.max_failure_rate;
+    if let Some(min_executions) = payload.min_executions {
+        sla.min_executions = min_executions;
+    }
+    if let Some(enabled) = payload.enabled {
+        sla.enabled = enabled;
+    }
+
+    let sla = state.job_queue.sla_monitor().slas().save(&sla).await?;
+    state
+        .publish(user_event(
+            Some(&user),
+            "job.sla_saved",
+            serde_json::json!({ "job_type": sla.job_type }),
+        ))
+        .await;
+
+    Ok(json(sla))
+}
+
+/// Stop checking a job type against an SLA
+async fn delete_job_sla_handler(
+    user: AuthUser,
+    axum::extract::Path(job_type): axum::extract::Path<String>,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_jobs_admin(&user)?;
+
+    let monitor = state.job_queue.sla_monitor();
+    if !monitor.slas().delete(&job_type).await? {
+        return Err(HttpError::not_found("Job SLA not found"));
+    }
+    Ok(no_content())
+}
+
+// =============================================================================
+// Fault Injection Routes and Handlers
+// =============================================================================
+
+use rustpress_core::fault::FaultRule;
+
+/// Outbound HTTP policies, with request, retry and circuit breaker
+/// counters for every destination contacted since startup
+async fn outbound_http_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can view outbound HTTP metrics",
+        ));
+    }
+
+    Ok(json(serde_json::json!({
+        "config": state.http.config(),
+        "destinations": state.http.metrics(),
+    })))
+}
+
+/// Fault injection routes
+fn fault_routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/",
+            get(list_faults_handler)
+                .post(add_fault_handler)
+                .delete(clear_faults_handler),
+        )
+        .route("/:id", delete(remove_fault_handler))
+}
+
+/// Only administrators may inject faults, and only where it is allowed
+fn require_fault_admin(user: &AuthUser, state: &AppState) -> HttpResult<()> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can manage fault injection",
+        ));
+    }
+    if !state.faults.is_allowed() {
+        return Err(HttpError::forbidden(
+            "Fault injection is only available in development and staging",
+        ));
+    }
+    Ok(())
+}
+
+/// List fault rules and how often each has fired
+async fn list_faults_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_fault_admin(&user, &state)?;
+
+    Ok(json(serde_json::json!({
+        "environment": current_environment(),
+        "rules": state.faults.rules(),
+        "hits": state.faults.hits(),
+    })))
+}
+
+/// Add a fault rule
+async fn add_fault_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(rule): Json<FaultRule>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_fault_admin(&user, &state)?;
+
+    let rule = state.faults.add_rule(rule)?;
+    Ok(created(rule))
+}
+
+/// Remove a fault rule
+async fn remove_fault_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    PathId(id): PathId,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_fault_admin(&user, &state)?;
+
+    if !state.faults.remove_rule(id) {
+        return Err(HttpError::not_found("Fault rule not found"));
+    }
+    Ok(json(serde_json::json!({ "success": true })))
+}
+
+/// Remove every fault rule
+async fn clear_faults_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_fault_admin(&user, &state)?;
+
+    state.faults.clear();
+    Ok(json(serde_json::json!({ "success": true })))
+}
+
+// =============================================================================
+// Device Login Handlers
+// =============================================================================
+
+use rustpress_auth::DevicePoll;
+
+#[derive(Debug, Deserialize)]
+struct DeviceCodeRequest {
+    client_id: String,
+    #[serde(default)]
+    scope: Option<String>,
+}
+
+/// Start a device login: returns the device code to poll with and the user
+/// code to enter in the browser
+async fn device_code_handler(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    Json(request): Json<DeviceCodeRequest>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let scopes = request
+        .scope
+        .as_deref()
+        .unwrap_or_default()
+        .split_whitespace()
+        .map(str::to_string)
+        .collect();
+
+    let mut response = state
+        .device_login
+        .create_device_authorization(&request.client_id, None, scopes)
+        .await?;
+
+    // Point the user at this server rather than a bare path
+    if let Some(origin) = request_origin(&state, &headers) {
+        response.verification_uri = format!("{}{}", origin, response.verification_uri);
+        response.verification_uri_complete = response
+            .verification_uri_complete
+            .map(|uri| format!("{}{}", origin, uri));
+    }
+
+    Ok(json(response))
+}
+
+/// Scheme and host the request was made to, honoring proxy headers
+fn request_origin(state: &AppState, headers: &axum::http::HeaderMap) -> Option<String> {
+    let host = headers.get(header::HOST)?.to_str().ok()?;
+    let scheme = headers
+        .get("x-forwarded-proto")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(',').next())
+        .map(str::trim)
+        .unwrap_or(if state.config().server.tls_enabled {
+            "https"
+        } else {
+            "http"
+        });
+    Some(format!("{}://{}", scheme, host))
+}
+
+#[derive(Debug, Deserialize)]
+struct DeviceTokenRequest {
+    client_id: String,
+    device_code: String,
+}
+
+/// Poll for the tokens of a device login. Until the user decides, this
+/// answers with an RFC 8628 error such as `authorization_pending`.
+async fn device_token_handler(
+    State(state): State<AppState>,
+    Json(request): Json<DeviceTokenRequest>,
+) -> HttpResult<Response> {
+    let poll = state
+        .device_login
+        .poll_device_code(&request.device_code, &request.client_id, None)
+        .await?;
+
+    let (error, description) = match poll {
+        DevicePoll::Complete(auth) => {
+            let user_id = auth
+                .user_id
+                .ok_or_else(|| rustpress_core::error::Error::internal("Approval has no user"))?;
+            return Ok(Json(user_token_response(&state, user_id).await?).into_response());
+        }
+        DevicePoll::Pending => (
+            "authorization_pending",
+            "The user has not approved the request yet",
+        ),
+        DevicePoll::SlowDown => ("slow_down", "Polling too quickly"),
+        DevicePoll::Denied => ("access_denied", "The user denied the request"),
+        DevicePoll::Expired => ("expired_token", "The device code has expired"),
+    };
+
+    Ok((
+        axum::http::StatusCode::BAD_REQUEST,
+        Json(serde_json::json!({
+            "error": error,
+            "error_description": description,
+        })),
+    )
+        .into_response())
+}
+
+/// Issue access and refresh tokens for an active user, as a login does
+async fn user_token_response(state: &AppState, user_id: Uuid) -> HttpResult<TokenResponse> {
+    let user: Option<rustpress_database::repository::users::UserRow> = sqlx::query_as(
+        r#"
+        SELECT id, email, username, password_hash, display_name, status, role,
+               avatar_url, locale, timezone,
+               email_verified_at, last_login_at, created_at, updated_at, deleted_at
+        FROM users
+        WHERE id = $1 AND deleted_at IS NULL
+        "#,
+    )
+    .bind(user_id)
+    .fetch_optional(state.db().inner())
+    .await
+    .map_err(|e| rustpress_core::error::Error::database_with_source("Failed to find user", e))?;
+
+    let user = user.ok_or_else(|| rustpress_core::error::Error::unauthorized("User not found"))?;
+    if user.status != "active" {
+        return Err(rustpress_core::error::Error::forbidden("Account is not active").into());
+    }
+
+    let jwt_manager = state.jwt();
+    let user_id_str = user.id.to_string();
+    let token = jwt_manager
+        .generate_access_token(&user_id_str, Some(&user.role), None)
+        .map_err(|e| {
+            rustpress_core::error::Error::internal(format!("Failed to generate token: {}", e))
+        })?;
+    let refresh = jwt_manager
+        .generate_refresh_token(&user_id_str)
+        .map_err(|e| {
+            rustpress_core::error::Error::internal(format!(
+                "Failed to generate refresh token: {}",
+                e
+            ))
+        })?;
+
+    Ok(TokenResponse {
+        access_token: token,
+        refresh_token: Some(refresh),
+        token_type: "Bearer".to_string(),
+        expires_in: 3600,
+        user: AuthUserResponse {
+            id: user.id,
+            email: user.email,
+            username: user.username,
+            display_name: user.display_name,
+            role: user.role,
+        },
+    })
+}
+
+#[derive(Debug, Deserialize)]
+struct DeviceUserCodeQuery {
+    user_code: String,
+}
+
+/// Show what a pending device login is asking for, before the user approves
+async fn get_device_authorization_handler(
+    _user: AuthUser,
+    State(state): State<AppState>,
+    Query(query): Query<DeviceUserCodeQuery>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let auth = state
+        .device_login
+        .find_device_authorization(&query.user_code)
+        .await?;
+
+    Ok(json(serde_json::json!({
+        "user_code": auth.user_code,
+        "client_id": auth.client_id,
+        "scopes": auth.scopes,
+        "expires_at": auth.expires_at,
+    })))
+}
+
+#[derive(Debug, Deserialize)]
+struct ResolveDeviceAuthorizationRequest {
+    user_code: String,
+    approve: bool,
+}
+
+/// Approve or deny a device login as the signed-in user
+async fn resolve_device_authorization_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(request): Json<ResolveDeviceAuthorizationRequest>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let auth = state
+        .device_login
+        .resolve_device_authorization(&request.user_code, user.id, request.approve)
+        .await?;
+
+    tracing::info!(
+        user_id = %user.id,
+        client_id = %auth.client_id,
+        approved = request.approve,
+        "Device login resolved"
+    );
+
+    Ok(json(serde_json::json!({
+        "user_code": auth.user_code,
+        "approved": request.approve,
+    })))
+}
+
+// =============================================================================
+// SAML Single Sign-On Handlers
+// =============================================================================
+
+use crate::services::saml_sso::is_local_redirect;
+use crate::services::SamlSsoConfig;
+
+/// Service provider metadata to register this site with an identity provider
+async fn saml_metadata_handler(State(state): State<AppState>) -> impl IntoResponse {
+    let client = state
+        .saml_sso
+        .client(&state.renderer().site_url().await)
+        .await;
+    (
+        [(header::CONTENT_TYPE, "application/samlmetadata+xml")],
+        client.metadata_xml(),
+    )
+}
+
+#[derive(Debug, Deserialize)]
+struct SamlLoginQuery {
+    /// Path on this site to return to once signed in
+    redirect_to: Option<String>,
+}
+
+/// Start a login by redirecting to the identity provider
+async fn saml_login_handler(
+    State(state): State<AppState>,
+    axum::extract::Path(provider): axum::extract::Path<String>,
+    Query(query): Query<SamlLoginQuery>,
+) -> HttpResult<impl IntoResponse> {
+    if let Some(target) = &query.redirect_to {
+        if !is_local_redirect(target) {
+            return Err(HttpError::bad_request(
+                "redirect_to must be a path on this site",
+            ));
+        }
+    }
+
+    let client = state
+        .saml_sso
+        .client(&state.renderer().site_url().await)
+        .await;
+    let (url, _) = client
+        .get_authn_request_url(&provider, query.redirect_to)
+        .await?;
+    Ok(axum::response::Redirect::to(&url))
+}
+
+#[derive(Debug, Deserialize)]
+struct SamlAcsForm {
+    #[serde(rename = "SAMLResponse")]
+    saml_response: String,
+}
+
+/// Assertion consumer service: verify the identity provider's response and
+/// hand the browser its tokens in the URL fragment, which is never sent to
+/// a server
+async fn saml_acs_handler(
+    State(state): State<AppState>,
+    axum::Form(form): axum::Form<SamlAcsForm>,
+) -> HttpResult<impl IntoResponse> {
+    let client = state
+        .saml_sso
+        .client(&state.renderer().site_url().await)
+        .await;
+    let info = client.handle_response(&form.saml_response).await?;
+    let user_id = state.saml_sso.sign_in(&info).await?;
+    let tokens = user_token_response(&state, user_id).await?;
+
+    tracing::info!(user_id = %user_id, provider = %info.provider, "SAML login");
+
+    let target = info
+        .redirect_after
+        .filter(|target| is_local_redirect(target))
+        .unwrap_or_else(|| "/admin".to_string());
+    let mut fragment = format!(
+        "access_token={}&token_type={}&expires_in={}",
+        urlencoding::encode(&tokens.access_token),
+        tokens.token_type,
+        tokens.expires_in
+    );
+    if let Some(refresh) = &tokens.refresh_token {
+        fragment.push_str(&format!("&refresh_token={}", urlencoding::encode(refresh)));
+    }
+    Ok(axum::response::Redirect::to(&format!(
+        "{}#{}",
+        target, fragment
+    )))
+}
+
+/// Get the configured identity providers
+async fn get_saml_config_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can view SAML settings",
+        ));
+    }
+
+    Ok(json(state.saml_sso.config().await.as_ref().clone()))
+}
+
+/// Replace the configured identity providers
+async fn update_saml_config_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(config): Json<SamlSsoConfig>,
+) -> HttpResult<impl IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can change SAML settings",
+        ));
+    }
+
+    config.validate()?;
+    config.save(state.db().inner()).await?;
+    state.saml_sso.set_config(config.clone()).await;
+    tracing::info!(user_id = %user.id, "SAML settings updated");
+
+    Ok(json(config))
+}
+
+// =============================================================================
+// User API Key Routes and Handlers
+// =============================================================================
+
+use crate::services::{ApiCapabilities, UserApiKeyInput};
+
+/// Keys manage keys only from a session, so a leaked key cannot mint more
+fn require_session(user: &AuthUser) -> HttpResult<()> {
+    if user.api_key_id().is_some() {
+        Err(HttpError::forbidden(
+            "API keys can only be managed from a signed-in session",
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// What the caller may do through the API, for clients such as the CLI in
+/// remote mode to check before they act
+async fn capabilities_handler(user: AuthUser) -> HttpResult<impl axum::response::IntoResponse> {
+    let scopes = user.api_key_scopes();
+    Ok(json(ApiCapabilities::new(
+        user.id,
+        user.email.clone(),
+        user.roles.clone(),
+        scopes.as_deref(),
+    )))
+}
+
+async fn list_user_api_keys_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_session(&user)?;
+    Ok(json(state.user_api_keys.list_keys(user.id).await?))
+}
+
+/// Create a key for the signed-in user; the response holds the only copy of
+/// its secret
+async fn create_user_api_key_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(payload): Json<UserApiKeyInput>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_session(&user)?;
+    let key = state.user_api_keys.create_key(user.id, payload).await?;
+    tracing::info!(key_id = %key.key.id, user_id = %user.id, "User API key created");
+    state
+        .publish(user_event(
+            Some(&user),
+            "api_key.created",
+            serde_json::json!({
+                "key_id": key.key.id,
+                "name": key.key.name,
+                "scopes": key.key.scopes.0,
+            }),
+        ))
+        .await;
+    Ok(created(key))
+}
+
+/// Revoke one of the user's keys; administrators may revoke anyone's
+async fn revoke_user_api_key_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_session(&user)?;
+    let key = state
+        .user_api_keys
+        .revoke_key(id, user.id, user.is_admin())
+        .await?;
+    tracing::info!(key_id = %key.id, user_id = %user.id, "User API key revoked");
+    state
+        .publish(user_event(
+            Some(&user),
+            "api_key.revoked",
+            serde_json::json!({ "key_id": key.id, "owner_id": key.user_id }),
+        ))
+        .await;
+    Ok(json(key))
+}
+
+// =============================================================================
+// Group Routes and Handlers
+// =============================================================================
+
+use crate::extract::require_permission;
+use crate::services::{GroupInput, GroupRecipient};
+use rustpress_auth::Permission;
+use crate::ws::RECIPIENTS_METADATA;
+
+/// Longest notification title
+const MAX_NOTIFICATION_TITLE: usize = 200;
+
+/// Longest notification message
+const MAX_NOTIFICATION_MESSAGE: usize = 5000;
+
+/// User group routes
+fn group_routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_groups_handler).post(create_group_handler))
+        .route("/mine", get(my_groups_handler))
+        .route(
+            "/:id",
+            get(get_group_handler)
+                .put(update_group_handler)
+                .delete(delete_group_handler),
+        )
+        .route(
+            "/:id/members",
+            get(list_group_members_handler).post(add_group_members_handler),
+        )
+        .route("/:id/members/:user_id", delete(remove_group_member_handler))
+        .route(
+            "/:id/sections",
+            get(list_group_sections_handler).post(add_group_section_handler),
+        )
+        .route(
+            "/:id/sections/:category_id",
+            delete(remove_group_section_handler),
+        )
+        .route("/:id/notify", post(notify_group_handler))
+}
+
+/// Users to add to a group
+#[derive(Debug, Deserialize)]
+struct AddGroupMembersRequest {
+    user_ids: Vec<Uuid>,
+    /// Whether they manage the group; re-adding a member changes it
+    #[serde(default)]
+    manager: bool,
+}
+
+/// Category to give a group ownership of
+#[derive(Debug, Deserialize)]
+struct AddGroupSectionRequest {
+    category_id: Uuid,
+}
+
+/// Message for every member of a group
+#[derive(Debug, Deserialize)]
+struct GroupNotifyRequest {
+    title: String,
+    message: String,
+    /// Site path or absolute http(s) URL
+    #[serde(default)]
+    link: Option<String>,
+    /// Also email the members
+    #[serde(default)]
+    email: bool,
+}
+
+impl GroupNotifyRequest {
+    fn normalize(mut self) -> HttpResult<Self> {
+        self.title = self.title.trim().to_string();
+        self.message = self.message.trim().to_string();
+        if self.title.is_empty() || self.title.chars().count() > MAX_NOTIFICATION_TITLE {
+            return Err(HttpError::bad_request(format!(
+                "Title is required and must be at most {} characters",
+                MAX_NOTIFICATION_TITLE
+            )));
+        }
+        if self.message.is_empty() || self.message.chars().count() > MAX_NOTIFICATION_MESSAGE {
+            return Err(HttpError::bad_request(format!(
+                "Message is required and must be at most {} characters",
+                MAX_NOTIFICATION_MESSAGE
+            )));
+        }
+        self.link = self
+            .link
+            .map(|l| l.trim().to_string())
+            .filter(|l| !l.is_empty());
+        if let Some(link) = &self.link {
+            let site_path = link.starts_with('/') && !link.starts_with("//");
+            if !site_path && !link.starts_with("https://") && !link.starts_with("http://") {
+                return Err(HttpError::bad_request(
+                    "Link must be a site path or an http(s) URL",
+                ));
+            }
+        }
+        Ok(self)
+    }
+}
+
+/// Administrators and holders of `groups:manage`
+async fn require_group_admin(user: &AuthUser, state: &AppState) -> HttpResult<()> {
+    require_permission(user, state, "groups", "manage").await
+}
+
+/// Only administrators may hand out roles and capabilities they don't
+/// hold themselves, so that managing groups can't be used to escalate
+fn require_held_grants(
+    user: &AuthUser,
+    state: &AppState,
+    roles: &[String],
+    capabilities: &[String],
+) -> HttpResult<()> {
+    if user.is_admin() {
+        return Ok(());
+    }
+    if let Some(role) = roles.iter().map(|r| r.trim()).find(|r| !user.has_role(r)) {
+        return Err(HttpError::forbidden(format!(
+            "Only administrators may grant the role '{}'",
+            role
+        )));
+    }
+    for capability in capabilities {
+        let Ok(permission) = capability.trim().parse::<Permission>() else {
+            continue;
+        };
+        let (resource, action) = (&permission.resource, &permission.action);
+        if !state.permissions.can(&user.roles, resource, action)
+            && !user.has_capability(resource, action)
+        {
+            return Err(HttpError::forbidden(format!(
+                "Only administrators may grant the capability '{}'",
+                permission
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Group administrators and the group's managers
+async fn require_group_manager(user: &AuthUser, state: &AppState, id: Uuid) -> HttpResult<()> {
+    if user.groups.manages(id) {
+        return Ok(());
+    }
+    require_group_admin(user, state).await
+}
+
+/// Group administrators and the group's members
+async fn require_group_member(user: &AuthUser, state: &AppState, id: Uuid) -> HttpResult<()> {
+    if user.groups.is_member(id) {
+        return Ok(());
+    }
+    require_group_admin(user, state).await
+}
+
+/// Deliver a `group.notification` event to the live connections of every
+/// active member of the groups; returns the members addressed
+async fn publish_group_notification(
+    state: &AppState,
+    user: &AuthUser,
+    group_ids: &[Uuid],
+    payload: serde_json::Value,
+) -> rustpress_core::error::Result<Vec<GroupRecipient>> {
+    let recipients = state.groups.recipients(group_ids).await?;
+    if recipients.is_empty() {
+        return Ok(recipients);
+    }
+    let recipient_ids: Vec<Uuid> = recipients.iter().map(|r| r.user_id).collect();
+    state
+        .publish(
+            user_event(Some(user), "group.notification", payload)
+                .with_metadata(RECIPIENTS_METADATA, serde_json::json!(recipient_ids)),
+        )
+        .await;
+    Ok(recipients)
+}
+
+async fn list_groups_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_group_admin(&user, &state).await?;
+    Ok(json(state.groups.list().await?))
+}
+
+/// Groups the signed-in user belongs to
+async fn my_groups_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    Ok(json(state.groups.for_user(user.id).await?))
+}
+
+async fn create_group_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(payload): Json<GroupInput>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_group_admin(&user, &state).await?;
+    require_held_grants(&user, &state, &payload.roles, &payload.capabilities)?;
+    let group = state.groups.create(payload, &state.permissions).await?;
+    tracing::info!(group_id = %group.id, user_id = %user.id, "Group created");
+    state
+        .publish(user_event(
+            Some(&user),
+            "group.created",
+            serde_json::json!({
+                "group_id": group.id,
+                "name": group.name,
+                "roles": group.roles.0,
+                "capabilities": group.capabilities.0,
+            }),
+        ))
+        .await;
+    Ok(created(group))
+}
+
+async fn get_group_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_group_member(&user, &state, id).await?;
+    Ok(json(state.groups.get(id).await?))
+}
+
+/// Replace a group's name, description and grants
+async fn update_group_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+    Json(payload): Json<GroupInput>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_group_admin(&user, &state).await?;
+    require_held_grants(&user, &state, &payload.roles, &payload.capabilities)?;
+    let group = state.groups.update(id, payload, &state.permissions).await?;
+    state
+        .publish(user_event(
+            Some(&user),
+            "group.updated",
+            serde_json::json!({
+                "group_id": group.id,
+                "name": group.name,
+                "roles": group.roles.0,
+                "capabilities": group.capabilities.0,
+            }),
+        ))
+        .await;
+    Ok(json(group))
+}
+
+async fn delete_group_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_group_admin(&user, &state).await?;
+    state.groups.delete(id).await?;
+    tracing::info!(group_id = %id, user_id = %user.id, "Group deleted");
+    state
+        .publish(user_event(
+            Some(&user),
+            "group.deleted",
+            serde_json::json!({ "group_id": id }),
+        ))
+        .await;
+    Ok(no_content())
+}
+
+async fn list_group_members_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_group_member(&user, &state, id).await?;
+    Ok(json(state.groups.members(id).await?))
+}
+
+/// Add users to a group (group administrators and its managers)
+async fn add_group_members_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+    Json(payload): Json<AddGroupMembersRequest>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_group_manager(&user, &state, id).await?;
+    let group = state.groups.get(id).await?;
+    require_held_grants(&user, &state, &group.roles, &group.capabilities)?;
+    let members = state
+        .groups
+        .add_members(id, &payload.user_ids, payload.manager)
+        .await?;
+    state
+        .publish(user_event(
+            Some(&user),
+            "group.members_added",
+            serde_json::json!({
+                "group_id": id,
+                "user_ids": payload.user_ids,
+                "manager": payload.manager,
+            }),
+        ))
+        .await;
+    Ok(json(members))
+}
+
+/// Remove a user from a group (group administrators and its managers)
+async fn remove_group_member_handler(
+    user: AuthUser,
+    axum::extract::Path((id, member_id)): axum::extract::Path<(Uuid, Uuid)>,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_group_manager(&user, &state, id).await?;
+    state.groups.remove_member(id, member_id).await?;
+    state
+        .publish(user_event(
+            Some(&user),
+            "group.member_removed",
+            serde_json::json!({ "group_id": id, "user_id": member_id }),
+        ))
+        .await;
+    Ok(no_content())
+}
+
+async fn list_group_sections_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_group_member(&user, &state, id).await?;
+    Ok(json(state.groups.sections(id).await?))
+}
+
+/// Give a group ownership of a category and its subcategories
+async fn add_group_section_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+    Json(payload): Json<AddGroupSectionRequest>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_group_admin(&user, &state).await?;
+    let sections = state.groups.add_section(id, payload.category_id).await?;
+    state
+        .publish(user_event(
+            Some(&user),
+            "group.section_added",
+            serde_json::json!({ "group_id": id, "category_id": payload.category_id }),
+        ))
+        .await;
+    Ok(json(sections))
+}
+
+async fn remove_group_section_handler(
+    user: AuthUser,
+    axum::extract::Path((id, category_id)): axum::extract::Path<(Uuid, Uuid)>,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_group_admin(&user, &state).await?;
+    state.groups.remove_section(id, category_id).await?;
+    state
+        .publish(user_event(
+            Some(&user),
+            "group.section_removed",
+            serde_json::json!({ "group_id": id, "category_id": category_id }),
+        ))
+        .await;
+    Ok(no_content())
+}
+
+/// Notify every active member of a group live, and by email on request
+/// (group administrators and its managers)
+async fn notify_group_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+    Json(payload): Json<GroupNotifyRequest>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_group_manager(&user, &state, id).await?;
+    let payload = payload.normalize()?;
+    let group = state.groups.get(id).await?;
+
+    let recipients = publish_group_notification(
+        &state,
+        &user,
+        &[id],
+        serde_json::json!({
+            "group_ids": [id],
+            "group_name": group.name,
+            "title": payload.title,
+            "message": payload.message,
+            "link": payload.link,
+            "sender_id": user.id,
+        }),
+    )
+    .await?;
+
+    // Emails go out in the background; a failed one doesn't stop the rest
+    let emailed = payload.email && !recipients.is_empty() && state.email_service.is_enabled().await;
+    if emailed {
+        let email_service = state.email_service.clone();
+        let recipients = recipients.clone();
+        tokio::spawn(async move {
+            for recipient in recipients {
+                if let Err(e) = email_service
+                    .send_group_notification(
+                        &recipient.email,
+                        recipient.display_name.as_deref(),
+                        &group.name,
+                        &payload.title,
+                        &payload.message,
+                        payload.link.as_deref(),
+                    )
+                    .await
+                {
+                    tracing::warn!(
+                        group_id = %group.id,
+                        user_id = %recipient.user_id,
+                        "Failed to email group notification: {}",
+                        e
+                    );
+                }
+            }
+        });
+    }
+
+    Ok(json(serde_json::json!({
+        "recipients": recipients.len(),
+        "emailed": emailed,
+    })))
+}
+
+// =============================================================================
+// Redirect Routes and Handlers
+// =============================================================================
+
+use crate::services::{RedirectInput, RedirectQuery};
+
+/// Redirect rule routes
+fn redirect_routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/",
+            get(list_redirects_handler).post(create_redirect_handler),
+        )
+        .route("/test", get(test_redirect_handler))
+        .route(
+            "/:id",
+            get(get_redirect_handler)
+                .put(update_redirect_handler)
+                .delete(delete_redirect_handler),
+        )
+}
+
+/// Path to try the rules against
+#[derive(Debug, Deserialize)]
+struct RedirectTestQuery {
+    path: String,
+}
+
+async fn list_redirects_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Query(query): Query<RedirectQuery>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_permission(&user, &state, "redirects", "manage").await?;
+    let (redirects, total) = state.redirects.list(&query).await?;
+    Ok(paginated(
+        redirects,
+        total,
+        query.page.unwrap_or(1).max(1),
+        query.per_page.unwrap_or(20).clamp(1, 100),
+    ))
+}
+
+async fn create_redirect_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(payload): Json<RedirectInput>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_permission(&user, &state, "redirects", "manage").await?;
+    let redirect = state.redirects.create(payload).await?;
+    state
+        .publish(user_event(
+            Some(&user),
+            "redirect.created",
+            serde_json::json!({
+                "redirect_id": redirect.id,
+                "source_path": redirect.source_path,
+                "match_type": redirect.match_type,
+                "status_code": redirect.status_code,
+            }),
+        ))
+        .await;
+    Ok(created(redirect))
+}
+
+/// Which rule a path would match, without counting a hit
+async fn test_redirect_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Query(query): Query<RedirectTestQuery>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_permission(&user, &state, "redirects", "manage").await?;
+    Ok(json(state.redirects.test(&query.path).await?))
+}
+
+async fn get_redirect_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_permission(&user, &state, "redirects", "manage").await?;
+    Ok(json(state.redirects.get(id).await?))
+}
+
+async fn update_redirect_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+    Json(payload): Json<RedirectInput>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_permission(&user, &state, "redirects", "manage").await?;
+    let redirect = state.redirects.update(id, payload).await?;
+    state
+        .publish(user_event(
+            Some(&user),
+            "redirect.updated",
+            serde_json::json!({
+                "redirect_id": redirect.id,
+                "source_path": redirect.source_path,
+                "match_type": redirect.match_type,
+                "status_code": redirect.status_code,
+                "enabled": redirect.enabled,
+            }),
+        ))
+        .await;
+    Ok(json(redirect))
+}
+
+async fn delete_redirect_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_permission(&user, &state, "redirects", "manage").await?;
+    state.redirects.delete(id).await?;
+    state
+        .publish(user_event(
+            Some(&user),
+            "redirect.deleted",
+            serde_json::json!({ "redirect_id": id }),
+        ))
+        .await;
+    Ok(no_content())
+}
+
+// =============================================================================
+// Editorial Discussion Routes and Handlers
+// =============================================================================
+
+use crate::services::{
+    CommentInput, DiscussionComment, DiscussionQuery, DiscussionThread, ThreadInput, ThreadStatus,
+};
+
+/// Characters of a comment quoted in mention notifications
+const MENTION_EXCERPT_LENGTH: usize = 200;
+
+/// Discussions are for those who may edit the post
+async fn require_discussion_access(
+    state: &AppState,
+    user: &AuthUser,
+    post_id: Uuid,
+) -> HttpResult<()> {
+    require_permission(user, state, "posts", "edit").await?;
+    check_section_access(state, user, Some(post_id), None).await?;
+    Ok(())
+}
+
+/// Deliver a `discussion.mention` event to the live connections of users
+/// newly mentioned in a comment, leaving out its author
+async fn publish_mentions(
+    state: &AppState,
+    user: &AuthUser,
+    thread: &DiscussionThread,
+    comment: &DiscussionComment,
+    already_mentioned: &[Uuid],
+) {
+    let recipients: Vec<Uuid> = comment
+        .mentions
+        .iter()
+        .filter(|id| **id != user.id && !already_mentioned.contains(id))
+        .copied()
+        .collect();
+    if recipients.is_empty() {
+        return;
+    }
+    let excerpt: String = comment
+        .content
+        .chars()
+        .take(MENTION_EXCERPT_LENGTH)
+        .collect();
+    state
+        .publish(
+            user_event(
+                Some(user),
+                "discussion.mention",
+                serde_json::json!({
+                    "post_id": thread.post_id,
+                    "thread_id": thread.id,
+                    "comment_id": comment.id,
+                    "block_client_id": thread.block_client_id,
+                    "excerpt": excerpt,
+                    "sender_id": user.id,
+                }),
+            )
+            .with_metadata(RECIPIENTS_METADATA, serde_json::json!(recipients)),
+        )
+        .await;
+}
+
+/// Publish a change to a thread; only ids are sent so discussions stay
+/// private to those who can load them
+async fn publish_discussion_event(
+    state: &AppState,
+    user: &AuthUser,
+    event_type: &str,
+    thread: &DiscussionThread,
+    comment_id: Option<Uuid>,
+) {
+    state
+        .publish(
+            user_event(
+                Some(user),
+                event_type,
+                serde_json::json!({
+                    "post_id": thread.post_id,
+                    "thread_id": thread.id,
+                    "comment_id": comment_id,
+                    "block_client_id": thread.block_client_id,
+                    "status": thread.status,
+                }),
+            )
+            .with_aggregate(thread.post_id, "post"),
+        )
+        .await;
+}
+
+/// A post's discussion threads; open ones unless `status` says otherwise
+async fn list_discussions_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+    Query(query): Query<DiscussionQuery>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_discussion_access(&state, &user, id).await?;
+    Ok(json(state.discussions.list(id, &query).await?))
+}
+
+async fn open_discussion_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+    Json(payload): Json<ThreadInput>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_discussion_access(&state, &user, id).await?;
+    let thread = state.discussions.open(id, user.id, payload).await?;
+    if let Some(comment) = thread.comments.first() {
+        publish_mentions(&state, &user, &thread, comment, &[]).await;
+    }
+    let comment_id = thread.comments.first().map(|c| c.id);
+    publish_discussion_event(&state, &user, "discussion.opened", &thread, comment_id).await;
+    Ok(created(thread))
+}
+
+async fn get_discussion_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let thread = state.discussions.get(id).await?;
+    require_discussion_access(&state, &user, thread.post_id).await?;
+    Ok(json(thread))
+}
+
+/// Delete a thread (its opener and administrators)
+async fn delete_discussion_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let thread = state.discussions.get(id).await?;
+    require_discussion_access(&state, &user, thread.post_id).await?;
+    if thread.created_by != Some(user.id) && !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only its author can delete a discussion",
+        ));
+    }
+    state.discussions.delete(id).await?;
+    publish_discussion_event(&state, &user, "discussion.deleted", &thread, None).await;
+    Ok(no_content())
+}
+
+async fn reply_discussion_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+    Json(payload): Json<CommentInput>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let thread = state.discussions.get(id).await?;
+    require_discussion_access(&state, &user, thread.post_id).await?;
+    let comment = state.discussions.reply(id, user.id, payload).await?;
+    publish_mentions(&state, &user, &thread, &comment, &[]).await;
+    publish_discussion_event(
+        &state,
+        &user,
+        "discussion.replied",
+        &thread,
+        Some(comment.id),
+    )
+    .await;
+    Ok(created(comment))
+}
+
+async fn resolve_discussion_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    set_discussion_status(user, id, state, ThreadStatus::Resolved).await
+}
+
+async fn reopen_discussion_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    set_discussion_status(user, id, state, ThreadStatus::Open).await
+}
+
+async fn set_discussion_status(
+    user: AuthUser,
+    id: Uuid,
+    state: AppState,
+    status: ThreadStatus,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let thread = state.discussions.get(id).await?;
+    require_discussion_access(&state, &user, thread.post_id).await?;
+    let thread = state.discussions.set_status(id, status, user.id).await?;
+    let event_type = match status {
+        ThreadStatus::Resolved => "discussion.resolved",
+        ThreadStatus::Open => "discussion.reopened",
+    };
+    publish_discussion_event(&state, &user, event_type, &thread, None).await;
+    Ok(json(thread))
+}
+
+/// Change a comment (its author only); users it newly mentions are notified
+async fn edit_discussion_comment_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+    Json(payload): Json<CommentInput>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let existing = state.discussions.comment(id).await?;
+    let thread = state.discussions.get(existing.thread_id).await?;
+    require_discussion_access(&state, &user, thread.post_id).await?;
+    if existing.author_id != Some(user.id) {
+        return Err(HttpError::forbidden("Only its author can edit a comment"));
+    }
+    let comment = state.discussions.edit(id, payload).await?;
+    publish_mentions(&state, &user, &thread, &comment, &existing.mentions).await;
+    publish_discussion_event(&state, &user, "discussion.edited", &thread, Some(id)).await;
+    Ok(json(comment))
+}
+
+/// Delete a reply (its author and administrators)
+async fn delete_discussion_comment_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let existing = state.discussions.comment(id).await?;
+    let thread = state.discussions.get(existing.thread_id).await?;
+    require_discussion_access(&state, &user, thread.post_id).await?;
+    if existing.author_id != Some(user.id) && !user.is_admin() {
+        return Err(HttpError::forbidden("Only its author can delete a comment"));
+    }
+    state.discussions.delete_comment(id).await?;
+    publish_discussion_event(
+        &state,
+        &user,
+        "discussion.comment_deleted",
+        &thread,
+        Some(id),
+    )
+    .await;
+    Ok(no_content())
+}
+
+// =============================================================================
+// Network Allowlist Routes and Handlers
+// =============================================================================
+
+use crate::middleware::TenantId;
+use crate::services::AllowlistUpdate;
+use rustpress_core::tenant::{current_site, site_filter, with_site, ExtensionKind};
+
+/// Network-admin routes for theme and plugin allowlists
+fn network_allowlist_routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/",
+            get(list_allowlists_handler)
+                .put(update_allowlists_handler)
+                .post(update_allowlists_handler),
+        )
+        .route("/effective", get(effective_allowlist_handler))
+}
+
+/// Site administrators run their own site only; the network takes a
+/// network-level role
+fn require_network_admin(user: &AuthUser) -> HttpResult<()> {
+    if user.has_role(NETWORK_ADMIN_ROLE) {
+        Ok(())
+    } else {
+        Err(HttpError::forbidden(
+            "Only network administrators can manage the network",
+        ))
+    }
+}
+
+/// Check the tenant and site allowlists for the current request; the
+/// network list is also enforced by the theme and plugin managers
+async fn check_extension_allowed(
+    state: &AppState,
+    tenant: Option<&TenantId>,
+    kind: ExtensionKind,
+    id: &str,
+) -> HttpResult<()> {
+    state
+        .extension_allowlists
+        .check(
+            kind,
+            id,
+            tenant.map(|TenantId(tenant)| tenant.as_str()),
+            state.theme_manager().site_id(),
+        )
+        .await?;
+    Ok(())
+}
+
+/// List every network, tenant and site allowlist
+async fn list_allowlists_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_network_admin(&user)?;
+
+    let allowlists = state.extension_allowlists.list().await?;
+    Ok(json(serde_json::json!({ "allowlists": allowlists })))
+}
+
+#[derive(Debug, Deserialize)]
+struct AllowlistBulkRequest {
+    updates: Vec<AllowlistUpdate>,
+}
+
+/// Create, replace or delete allowlists in bulk
+async fn update_allowlists_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(request): Json<AllowlistBulkRequest>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_network_admin(&user)?;
+
+    let allowlists = state
+        .extension_allowlists
+        .bulk_update(request.updates, user.id)
+        .await?;
+    Ok(json(serde_json::json!({ "allowlists": allowlists })))
+}
+
+#[derive(Debug, Deserialize)]
+struct EffectiveAllowlistQuery {
+    tenant: Option<String>,
+    site: Option<Uuid>,
+}
+
+/// Themes and plugins a tenant and site end up with after every list applies
+async fn effective_allowlist_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Query(query): Query<EffectiveAllowlistQuery>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_network_admin(&user)?;
+
+    let allowlist = state
+        .extension_allowlists
+        .effective(query.tenant.as_deref(), query.site)
+        .await;
+    Ok(json(allowlist))
+}
+
+// =============================================================================
+// Network Site Routes and Handlers
+// =============================================================================
+
+use crate::services::sites::NETWORK_ADMIN_ROLE;
+use crate::services::{CurrentSite, SiteInput, SitePlanInput, SiteStatus};
+
+/// Network-admin routes for the sites of the network
+fn network_site_routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_sites_handler).post(create_site_handler))
+        .route(
+            "/:id",
+            get(get_site_handler)
+                .put(update_site_handler)
+                .delete(archive_site_handler),
+        )
+        .route("/:id/suspend", post(suspend_site_handler))
+        .route("/:id/activate", post(activate_site_handler))
+        .route("/:id/plugins", get(list_site_plugins_handler))
+        .route("/:id/plugins/:plugin", put(set_site_plugin_handler))
+        .route("/:id/members", get(list_site_members_handler))
+        .route(
+            "/:id/members/:user",
+            put(add_site_member_handler).delete(remove_site_member_handler),
+        )
+        .route("/:id/plan", put(set_site_plan_handler))
+        .route("/:id/usage", get(get_site_usage_handler))
+}
+
+/// Drop cached pages after a site changes how it is served
+async fn purge_site_pages(state: &AppState) {
+    if let Err(e) = state.page_cache.purge_all().await {
+        tracing::warn!("Failed to purge cached pages: {}", e);
+    }
+}
+
+/// List the sites of the network
+async fn list_sites_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_network_admin(&user)?;
+
+    let sites = state.sites.list().await?;
+    Ok(json(
+        serde_json::json!({ "sites": sites, "total": sites.len() }),
+    ))
+}
+
+/// Create a site with the main site's themes
+async fn create_site_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(payload): Json<SiteInput>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_network_admin(&user)?;
+
+    let site = state.sites.create(payload).await?;
+    Ok(created(site))
+}
+
+async fn get_site_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_network_admin(&user)?;
+
+    Ok(json(state.sites.get(id).await?))
+}
+
+async fn update_site_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+    Json(payload): Json<SiteInput>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_network_admin(&user)?;
+
+    let site = state.sites.update(id, payload).await?;
+    purge_site_pages(&state).await;
+    Ok(json(site))
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct SiteStatusRequest {
+    reason: Option<String>,
+}
+
+/// Take a site offline for good; its content is kept
+async fn archive_site_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+    payload: Option<Json<SiteStatusRequest>>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_network_admin(&user)?;
+
+    let reason = payload.and_then(|Json(p)| p.reason);
+    let site = state
+        .sites
+        .set_status(id, SiteStatus::Archived, reason)
+        .await?;
+    purge_site_pages(&state).await;
+    Ok(json(site))
+}
+
+/// Take a site offline until it is activated again
+async fn suspend_site_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+    payload: Option<Json<SiteStatusRequest>>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_network_admin(&user)?;
+
+    let reason = payload.and_then(|Json(p)| p.reason);
+    let site = state
+        .sites
+        .set_status(id, SiteStatus::Suspended, reason)
+        .await?;
+    purge_site_pages(&state).await;
+    Ok(json(site))
+}
+
+/// Bring a suspended or archived site back online
+async fn activate_site_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_network_admin(&user)?;
+
+    let site = state.sites.set_status(id, SiteStatus::Active, None).await?;
+    purge_site_pages(&state).await;
+    Ok(json(site))
+}
+
+/// Plugins a site activated besides the network's
+async fn list_site_plugins_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_network_admin(&user)?;
+
+    state.sites.get(id).await?;
+    let plugins = state.sites.plugins(id).await?;
+    Ok(json(serde_json::json!({ "site": id, "plugins": plugins })))
+}
+
+#[derive(Debug, Deserialize)]
+struct SitePluginRequest {
+    active: bool,
+}
+
+/// Activate or deactivate a plugin on a site
+async fn set_site_plugin_handler(
+    user: AuthUser,
+    axum::extract::Path((id, plugin)): axum::extract::Path<(Uuid, String)>,
+    State(state): State<AppState>,
+    Json(payload): Json<SitePluginRequest>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_network_admin(&user)?;
+
+    let site = state.sites.get(id).await?;
+    if payload.active {
+        if state.plugins.read().await.get(&plugin).is_none() {
+            return Err(HttpError::not_found(format!(
+                "Plugin '{}' not found",
+                plugin
+            )));
+        }
+        let tenant = site.tenant.as_deref().unwrap_or(&site.slug);
+        state
+            .extension_allowlists
+            .check(ExtensionKind::Plugin, &plugin, Some(tenant), Some(site.id))
+            .await?;
+    }
+
+    state.sites.set_plugin(id, &plugin, payload.active).await?;
+    purge_site_pages(&state).await;
+    Ok(json(
+        serde_json::json!({ "site": id, "plugin": plugin, "active": payload.active }),
+    ))
+}
+
+/// Users added to a site besides its owner
+async fn list_site_members_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_network_admin(&user)?;
+
+    let members = state.sites.members(id).await?;
+    Ok(json(serde_json::json!({ "site": id, "members": members })))
+}
+
+/// Let a user act on a site
+async fn add_site_member_handler(
+    user: AuthUser,
+    axum::extract::Path((id, member)): axum::extract::Path<(Uuid, Uuid)>,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_network_admin(&user)?;
+
+    state.sites.set_member(id, member, true).await?;
+    Ok(json(
+        serde_json::json!({ "site": id, "user": member, "member": true }),
+    ))
+}
+
+async fn remove_site_member_handler(
+    user: AuthUser,
+    axum::extract::Path((id, member)): axum::extract::Path<(Uuid, Uuid)>,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_network_admin(&user)?;
+
+    state.sites.set_member(id, member, false).await?;
+    Ok(no_content())
+}
+
+/// Put a site on a plan, or give it quotas of its own
+async fn set_site_plan_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+    Json(payload): Json<SitePlanInput>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_network_admin(&user)?;
+
+    Ok(json(state.usage.set_plan(id, payload).await?))
+}
+
+/// Quotas of a site and what it used this month and the months before
+async fn get_site_usage_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_network_admin(&user)?;
+
+    Ok(json(state.usage.report(id).await?))
+}
+
+#[derive(Debug, Deserialize)]
+struct NetworkUsageQuery {
+    /// Month as YYYY-MM, the current one by default
+    period: Option<String>,
+}
+
+/// Usage of every site in a month, for billing
+async fn network_usage_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Query(query): Query<NetworkUsageQuery>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_network_admin(&user)?;
+
+    let period = match query.period.as_deref() {
+        Some(period) => crate::services::usage::parse_period(period)?,
+        None => chrono::Utc::now().date_naive(),
+    };
+    let sites = state.usage.billing_report(period).await?;
+    Ok(json(serde_json::json!({
+        "period": crate::services::usage::month_of(period),
+        "sites": sites,
+    })))
+}
+
+/// Quotas and usage of the site being served, for its administrators
+async fn current_site_usage_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    site: Option<axum::Extension<CurrentSite>>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can view the site's usage",
+        ));
+    }
+    let Some(axum::Extension(CurrentSite(site))) = site else {
+        return Err(HttpError::not_found("The main site is not metered"));
+    };
+
+    Ok(json(state.usage.report(site.id).await?))
+}
+
+// =============================================================================
+// Content Sanitization Routes and Handlers
+// =============================================================================
+
+use crate::services::{
+    ContentSource, LegacySweep, ReportQuery, SanitizationPolicy, SanitizedContent,
+};
+
+/// Content sanitization routes
+fn sanitization_routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/policy",
+            get(get_sanitization_policy_handler).put(update_sanitization_policy_handler),
+        )
+        .route("/preview", post(preview_sanitization_handler))
+        .route("/reports", get(list_sanitization_reports_handler))
+        .route("/legacy-sweep", post(sweep_legacy_content_handler))
+}
+
+fn require_sanitization_admin(user: &AuthUser) -> HttpResult<()> {
+    if user.is_admin() {
+        Ok(())
+    } else {
+        Err(HttpError::forbidden(
+            "Only administrators can manage content sanitization",
+        ))
+    }
+}
+
+/// Get the sanitization policy
+async fn get_sanitization_policy_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_sanitization_admin(&user)?;
+    Ok(json(state.sanitization.policy().await.as_ref().clone()))
+}
+
+/// Update the sanitization policy
+async fn update_sanitization_policy_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(policy): Json<SanitizationPolicy>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_sanitization_admin(&user)?;
+    policy.validate()?;
+    policy.save(state.db().inner()).await?;
+    state.sanitization.set_policy(policy.clone()).await;
+    Ok(json(policy))
+}
+
+/// Sanitization preview request
+#[derive(Debug, serde::Deserialize)]
+struct SanitizationPreviewRequest {
+    html: String,
+    /// Role, `comment` or `legacy`; defaults to the caller's own policy
+    role: Option<String>,
+}
+
+/// Show what the policy would strip from some HTML without saving it
+async fn preview_sanitization_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(request): Json<SanitizationPreviewRequest>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let preview = match request.role {
+        Some(role) => {
+            require_sanitization_admin(&user)?;
+            state.sanitization.preview(&role, &request.html).await
+        }
+        None => {
+            state
+                .sanitization
+                .sanitize_for_roles(&user.roles, &request.html)
+                .await
+        }
+    };
+    Ok(json(preview))
+}
+
+/// List sanitization reports, newest first
+async fn list_sanitization_reports_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Query(query): Query<ReportQuery>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_sanitization_admin(&user)?;
+    let (reports, total) = state.sanitization.list_reports(&query).await?;
+    Ok(paginated(
+        reports,
+        total,
+        query.page.unwrap_or(1).max(1),
+        query.per_page.unwrap_or(20).clamp(1, 100),
+    ))
+}
+
+/// Legacy sweep request
+#[derive(Debug, serde::Deserialize)]
+struct LegacySweepRequest {
+    /// Continue after this post id (`next_after` of the previous batch)
+    after: Option<Uuid>,
+    limit: Option<i64>,
+    #[serde(default = "default_true")]
+    dry_run: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Sanitize legacy imported HTML in one batch of posts
+async fn sweep_legacy_content_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(request): Json<LegacySweepRequest>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_sanitization_admin(&user)?;
+    let sweep: LegacySweep = state
+        .sanitization
+        .sweep_legacy(
+            request.after,
+            request.limit.unwrap_or(50),
+            request.dry_run,
+            user.id,
+        )
+        .await?;
+    if !sweep.dry_run {
+        for item in &sweep.changed {
+            state.page_cache.purge_post(item.post_id, Vec::new()).await;
+        }
+    }
+    Ok(json(sweep))
+}
+
+// =============================================================================
+// GeoIP Routes and Handlers
+// =============================================================================
+
+use crate::services::GeoIpConfig;
+
+/// GeoIP routes
+fn geoip_routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/",
+            get(get_geoip_config_handler).put(update_geoip_config_handler),
+        )
+        .route("/status", get(geoip_status_handler))
+        .route("/update", post(update_geoip_database_handler))
+        .route("/lookup/:ip", get(geoip_lookup_handler))
+        .route("/me", get(geoip_me_handler))
+}
+
+/// Get the GeoIP configuration (license key masked)
+async fn get_geoip_config_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can view GeoIP settings",
+        ));
+    }
+
+    Ok(json(state.geoip.config().masked()))
+}
+
+/// Update the GeoIP configuration and reopen the databases
+async fn update_geoip_config_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(mut config): Json<GeoIpConfig>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can change GeoIP settings",
+        ));
+    }
+
+    config.merge_secret(&state.geoip.config());
+    config.validate()?;
+    config.save(state.db().inner()).await?;
+
+    let masked = config.masked();
+    state.geoip.set_config(config).await?;
+
+    Ok(json(masked))
+}
+
+/// Loaded databases and last update
+async fn geoip_status_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can view GeoIP status",
+        ));
+    }
+
+    Ok(json(state.geoip.status()))
+}
+
+/// Download fresh databases now
+async fn update_geoip_database_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can update GeoIP databases",
+        ));
+    }
+    if !state.geoip.config().enabled {
+        return Err(HttpError::bad_request("GeoIP is not enabled"));
+    }
+
+    let databases = state.geoip.update_databases().await?;
+    Ok(json(
+        serde_json::json!({ "success": true, "databases": databases }),
+    ))
+}
+
+/// Full location of any address
+async fn geoip_lookup_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    axum::extract::Path(ip): axum::extract::Path<String>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can look up addresses",
+        ));
+    }
+
+    let ip: std::net::IpAddr = ip
+        .parse()
+        .map_err(|_| HttpError::bad_request("Invalid IP address"))?;
+    if !state.geoip.is_available() {
+        return Err(HttpError::service_unavailable(
+            "No GeoIP database is loaded",
+        ));
+    }
+
+    Ok(json(serde_json::json!({
+        "ip": ip,
+        "location": state.geoip.lookup(ip),
+    })))
+}
+
+/// The caller's own location, at country level and with a truncated IP
+async fn geoip_me_handler(
+    State(state): State<AppState>,
+    request: axum::http::Request<axum::body::Body>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let ip = crate::middleware::client_ip(&request, &state.config.server.trusted_proxies);
+
+    let location = ip
+        .and_then(|ip| state.geoip.lookup(ip))
+        .and_then(|location| {
+            location.for_storage(&crate::services::GeoIpPrivacy {
+                store_city: false,
+                ..Default::default()
+            })
+        });
+
+    Ok(json(serde_json::json!({
+        "ip": ip.map(|ip| state.geoip.storage_ip(ip)),
+        "location": location,
+    })))
+}
+
+// =============================================================================
+// Compliance Routes and Handlers
+// =============================================================================
+
+use crate::services::{ComplianceConfig, ContentDescriptor, RegionContext};
+
+/// Regional compliance rule routes
+fn compliance_routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/",
+            get(get_compliance_rules_handler).put(update_compliance_rules_handler),
+        )
+        .route("/history", get(compliance_history_handler))
+        .route("/evaluate", post(evaluate_compliance_handler))
+        .route("/me", get(compliance_me_handler))
+}
+
+/// Get the compliance rule set
+async fn get_compliance_rules_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can view compliance rules",
+        ));
+    }
+
+    Ok(json(state.compliance.engine().await.config().clone()))
+}
+
+/// Replace the compliance rule set, recording a new revision
+async fn update_compliance_rules_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(config): Json<ComplianceConfig>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can change compliance rules",
+        ));
+    }
+
+    let config = state.compliance.update(config, user.id).await?;
+    Ok(json(config))
+}
+
+/// Previous revisions of the rule set, newest first
+async fn compliance_history_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can view compliance history",
+        ));
+    }
+
+    Ok(json(state.compliance.history().await?))
+}
+
+/// Dry-run request for the compliance rules
+#[derive(Debug, Deserialize)]
+struct EvaluateComplianceRequest {
+    /// Resolve the region from this IP through GeoIP
+    ip: Option<std::net::IpAddr>,
+    /// Or give the region directly
+    region: Option<RegionContext>,
+    /// Content shown; rules with content selectors only match when set
+    content: Option<ContentDescriptor>,
+    /// Evaluate these rules instead of the saved ones
+    rules: Option<ComplianceConfig>,
+}
+
+/// Evaluate rules for a location and content, with a per-rule trace
+async fn evaluate_compliance_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(payload): Json<EvaluateComplianceRequest>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can evaluate compliance rules",
+        ));
+    }
+
+    let region = match (payload.region, payload.ip) {
+        (Some(region), _) => region,
+        (None, ip) => state.compliance.region_for(ip),
+    };
+    let engine = match payload.rules {
+        Some(config) => {
+            config.validate()?;
+            Arc::new(crate::services::ComplianceEngine::new(config))
+        }
+        None => state.compliance.engine().await,
+    };
+    let (decision, trace) = engine.explain(&region, payload.content.as_ref());
+
+    Ok(json(serde_json::json!({
+        "region": region,
+        "decision": decision,
+        "trace": trace,
+    })))
+}
+
+/// Cookie banner variant and site-wide rules for the caller's region
+async fn compliance_me_handler(
+    State(state): State<AppState>,
+    request: axum::http::Request<axum::body::Body>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let ip = crate::middleware::client_ip(&request, &state.config.server.trusted_proxies);
+
+    let region = state.compliance.region_for(ip);
+    let decision = state.compliance.engine().await.evaluate(&region, None);
+
+    Ok(json(serde_json::json!({
+        "country_code": region.country_code,
+        "cookie_banner": decision.cookie_banner,
+        "age_gate": decision.age_gate,
+    })))
+}
+
+// =============================================================================
+// Export Routes and Handlers
+// =============================================================================
+
+use crate::services::{ExportDataset, ExportParams, ExportService, SiteBundle};
+
+/// Exports started per user per hour
+const EXPORT_RATE_LIMIT_PER_HOUR: i64 = 10;
+
+/// Streaming export routes
+fn export_routes() -> Router<AppState> {
+    Router::new()
+        .route("/site", get(export_site_bundle_handler))
+        .route("/site/import", post(import_site_bundle_handler))
+        .route("/:dataset", get(export_dataset_handler))
+}
+
+/// Site bundle with the data of every plugin that takes part in exports
+async fn export_site_bundle_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden("Only administrators can export data"));
+    }
+
+    let from_plugins = state.plugins.read().await.data_exporters();
+    let bundle = state.site_bundles.export(from_plugins).await?;
+    tracing::info!(
+        user_id = %user.id,
+        plugins = bundle.plugins.len(),
+        "Exported site bundle"
+    );
+    Ok(json(bundle))
+}
+
+/// Restore plugin data from a site bundle
+async fn import_site_bundle_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(bundle): Json<SiteBundle>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden("Only administrators can import data"));
+    }
+
+    let from_plugins = state.plugins.read().await.data_exporters();
+    let results = state.site_bundles.import(from_plugins, bundle).await?;
+    state
+        .publish(user_event(
+            Some(&user),
+            "site.bundle_imported",
+            serde_json::json!({ "plugins": results }),
+        ))
+        .await;
+    Ok(json(serde_json::json!({ "plugins": results })))
+}
+
+/// Stream a dataset as CSV or JSONL
+async fn export_dataset_handler(
+    user: AuthUser,
+    axum::extract::Path(dataset): axum::extract::Path<String>,
+    Query(params): Query<ExportParams>,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden("Only administrators can export data"));
+    }
+
+    let dataset: ExportDataset = dataset.parse()?;
+
+    // Count export starts per user in a fixed hourly window; resumed
+    // downloads count as new exports
+    let started = state
+        .cache()
+        .increment_window(
+            format!("export_rate:{}", user.id),
+            1,
+            std::time::Duration::from_secs(3600),
+        )
+        .await
+        .unwrap_or(0);
+    if started > EXPORT_RATE_LIMIT_PER_HOUR {
+        return Err(rustpress_core::error::Error::RateLimited {
+            retry_after_secs: 3600,
+        }
+        .into());
+    }
+
+    tracing::info!(
+        user_id = %user.id,
+        dataset = dataset.name(),
+        after = ?params.after,
+        "Starting export"
+    );
+
+    let service = ExportService::new(state.db().inner().clone());
+    Ok(service.stream(dataset, params)?)
+}
+
+// =============================================================================
+// WordPress Import Routes and Handlers
+// =============================================================================
+
+use crate::services::{WordpressImportOptions, MAX_WXR_SIZE};
+
+/// WordPress import routes
+fn import_routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/wordpress",
+            get(list_wordpress_imports_handler)
+                .post(start_wordpress_import_handler)
+                .layer(axum::extract::DefaultBodyLimit::max(MAX_WXR_SIZE)),
+        )
+        .route("/wordpress/:id", get(get_wordpress_import_handler))
+        .route(
+            "/wordpress/:id/redirects",
+            get(wordpress_import_redirects_handler),
+        )
+        // Imports write in batches as they go
+        .route_layer(axum::middleware::from_fn(
+            crate::transaction::without_request_transaction,
+        ))
+}
+
+/// Start importing a WXR file sent as the request body; poll the returned
+/// run for progress
+async fn start_wordpress_import_handler(
+    user: AuthUser,
+    Query(options): Query<WordpressImportOptions>,
+    State(state): State<AppState>,
+    body: String,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can import content",
+        ));
+    }
+
+    let run = state
+        .wordpress_imports
+        .start(user.id, options, body)
+        .await?;
+    tracing::info!(
+        import_id = %run.id,
+        user_id = %user.id,
+        total = run.total,
+        dry_run = run.dry_run,
+        "WordPress import started"
+    );
+    state
+        .publish(user_event(
+            Some(&user),
+            "import.wordpress_started",
+            serde_json::json!({
+                "import_id": run.id,
+                "source_url": run.source_url,
+                "total": run.total,
+                "dry_run": run.dry_run,
+            }),
+        ))
+        .await;
+    Ok(created(run))
+}
+
+/// Recent WordPress imports
+async fn list_wordpress_imports_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden("Only administrators can view imports"));
+    }
+
+    Ok(json(state.wordpress_imports.list(20).await?))
+}
+
+/// One WordPress import and its progress
+async fn get_wordpress_import_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden("Only administrators can view imports"));
+    }
+
+    Ok(json(state.wordpress_imports.get(id).await?))
+}
+
+/// Redirects an import created, as CSV for use in another web server
+async fn wordpress_import_redirects_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden("Only administrators can view imports"));
+    }
+
+    let csv = state.wordpress_imports.redirect_map(id).await?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"redirects-{}.csv\"", id),
+            ),
+        ],
+        csv,
+    ))
+}
+
+// =============================================================================
+// Public API Routes and Handlers
+// =============================================================================
+
+use crate::services::PublicApiKeyInput;
+
+/// Most items a public API list returns per page
+const PUBLIC_API_MAX_PER_PAGE: u32 = 50;
+
+/// Read-only routes of the public API tier
+fn public_api_routes(state: &AppState) -> Router<AppState> {
+    Router::new()
+        .route("/posts", get(public_list_posts_handler))
+        .route("/posts/:slug", get(public_get_post_handler))
+        .route("/pages", get(public_list_pages_handler))
+        .route("/pages/:slug", get(public_get_page_handler))
+        .route("/categories", get(public_list_categories_handler))
+        .route("/tags", get(public_list_tags_handler))
+        .route_layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            crate::middleware::public_api,
+        ))
+}
+
+/// Public API key management and usage analytics
+fn public_api_admin_routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/keys",
+            get(list_public_api_keys_handler).post(create_public_api_key_handler),
+        )
+        .route(
+            "/keys/:id",
+            get(get_public_api_key_handler)
+                .put(update_public_api_key_handler)
+                .delete(revoke_public_api_key_handler),
+        )
+        .route("/usage", get(public_api_usage_handler))
+}
+
+/// Published post or page in a public API list
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct PublicPostSummary {
+    id: Uuid,
+    title: String,
+    slug: String,
+    excerpt: Option<String>,
+    author: Option<String>,
+    published_at: Option<chrono::DateTime<chrono::Utc>>,
+    updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Published post or page with its content
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct PublicPost {
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    summary: PublicPostSummary,
+    content: Option<String>,
+}
+
+/// Category or tag with its published post count
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct PublicTerm {
+    id: Uuid,
+    name: String,
+    slug: String,
+    description: Option<String>,
+    post_count: i64,
+}
+
+/// Public post list filters
+#[derive(Debug, Deserialize)]
+struct PublicPostQuery {
+    #[serde(default = "default_public_page")]
+    page: u32,
+    #[serde(default = "default_public_per_page")]
+    per_page: u32,
+    /// Category slug
+    category: Option<String>,
+    /// Tag slug
+    tag: Option<String>,
+}
+
+fn default_public_page() -> u32 {
+    1
+}
+
+fn default_public_per_page() -> u32 {
+    10
+}
+
+/// Published posts of a type, newest first
+async fn public_posts(
+    state: &AppState,
+    post_type: &str,
+    query: PublicPostQuery,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let pool = state.db().inner();
+    let page = query.page.max(1);
+    let per_page = query.per_page.clamp(1, PUBLIC_API_MAX_PER_PAGE);
+    let offset = (page as i64 - 1) * per_page as i64;
+
+    const FILTER: &str = r#"
+        p.post_type = $1 AND p.status = 'published' AND p.deleted_at IS NULL AND {site}
+          AND ($2::text IS NULL OR EXISTS (
+                SELECT 1 FROM post_categories pc JOIN categories c ON c.id = pc.category_id
+                WHERE pc.post_id = p.id AND c.slug = $2))
+          AND ($3::text IS NULL OR EXISTS (
+                SELECT 1 FROM post_tags pt JOIN tags t ON t.id = pt.tag_id
+                WHERE pt.post_id = p.id AND t.slug = $3))
+    "#;
+
+    let filter = FILTER.replace("{site}", &site_filter("p.site_id"));
+    let posts: Vec<PublicPostSummary> = sqlx::query_as(&format!(
+        r#"
+        SELECT p.id, p.title, p.slug, p.excerpt,
+               COALESCE(NULLIF(u.display_name, ''), u.username) AS author,
+               p.published_at, p.updated_at
+        FROM posts p
+        LEFT JOIN users u ON u.id = p.author_id
+        WHERE {}
+        ORDER BY p.published_at DESC NULLS LAST, p.id
+        LIMIT $4 OFFSET $5
+        "#,
+        filter
+    ))
+    .bind(post_type)
+    .bind(&query.category)
+    .bind(&query.tag)
+    .bind(per_page as i64)
+    .bind(offset)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| rustpress_core::error::Error::database_with_source("Failed to list posts", e))?;
+
+    let total: (i64,) = sqlx::query_as(&format!("SELECT COUNT(*) FROM posts p WHERE {}", filter))
+        .bind(post_type)
+        .bind(&query.category)
+        .bind(&query.tag)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| {
+            rustpress_core::error::Error::database_with_source("Failed to count posts", e)
+        })?;
+
+    Ok(paginated(posts, total.0 as u64, page, per_page))
+}
+
+/// A published post or page by slug
+async fn public_post(
+    state: &AppState,
+    post_type: &str,
+    slug: &str,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let post: Option<PublicPost> = sqlx::query_as(&format!(
+        r#"
+        SELECT p.id, p.title, p.slug, p.excerpt, p.content,
+               COALESCE(NULLIF(u.display_name, ''), u.username) AS author,
+               p.published_at, p.updated_at
+        FROM posts p
+        LEFT JOIN users u ON u.id = p.author_id
+        WHERE p.post_type = $1 AND p.slug = $2 AND {}
+          AND p.status = 'published' AND p.deleted_at IS NULL
+        "#,
+        site_filter("p.site_id")
+    ))
+    .bind(post_type)
+    .bind(slug)
+    .fetch_optional(state.db().inner())
+    .await
+    .map_err(|e| rustpress_core::error::Error::database_with_source("Failed to load post", e))?;
+
+    let post = post.ok_or_else(|| rustpress_core::error::Error::not_found(post_type, slug))?;
+    Ok(json(post))
+}
+
+async fn public_list_posts_handler(
+    Query(query): Query<PublicPostQuery>,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    public_posts(&state, "post", query).await
+}
+
+async fn public_get_post_handler(
+    axum::extract::Path(slug): axum::extract::Path<String>,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    public_post(&state, "post", &slug).await
+}
+
+async fn public_list_pages_handler(
+    Query(query): Query<PublicPostQuery>,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    public_posts(&state, "page", query).await
+}
+
+async fn public_get_page_handler(
+    axum::extract::Path(slug): axum::extract::Path<String>,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    public_post(&state, "page", &slug).await
+}
+
+async fn public_list_categories_handler(
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let categories: Vec<PublicTerm> = sqlx::query_as(&format!(
+        r#"
+        SELECT c.id, c.name, c.slug, c.description,
+               COUNT(p.id) AS post_count
+        FROM categories c
+        LEFT JOIN post_categories pc ON pc.category_id = c.id
+        LEFT JOIN posts p ON p.id = pc.post_id
+             AND p.status = 'published' AND p.deleted_at IS NULL AND {}
+        GROUP BY c.id
+        ORDER BY c.name
+        "#,
+        site_filter("p.site_id")
+    ))
+    .fetch_all(state.db().inner())
+    .await
+    .map_err(|e| {
+        rustpress_core::error::Error::database_with_source("Failed to list categories", e)
+    })?;
+    Ok(json(categories))
+}
+
+async fn public_list_tags_handler(
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let tags: Vec<PublicTerm> = sqlx::query_as(&format!(
+        r#"
+        SELECT t.id, t.name, t.slug, t.description,
+               COUNT(p.id) AS post_count
+        FROM tags t
+        LEFT JOIN post_tags pt ON pt.tag_id = t.id
+        LEFT JOIN posts p ON p.id = pt.post_id
+             AND p.status = 'published' AND p.deleted_at IS NULL AND {}
+        GROUP BY t.id
+        ORDER BY t.name
+        "#,
+        site_filter("p.site_id")
+    ))
+    .fetch_all(state.db().inner())
+    .await
+    .map_err(|e| rustpress_core::error::Error::database_with_source("Failed to list tags", e))?;
+    Ok(json(tags))
+}
+
+fn require_public_api_admin(user: &AuthUser) -> HttpResult<()> {
+    if user.is_admin() {
+        Ok(())
+    } else {
+        Err(HttpError::forbidden(
+            "Only administrators can manage public API keys",
+        ))
+    }
+}
+
+async fn list_public_api_keys_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_public_api_admin(&user)?;
+    Ok(json(state.public_api.list_keys().await?))
+}
+
+/// Create a key; the response holds the only copy of its secret
+async fn create_public_api_key_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(payload): Json<PublicApiKeyInput>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_public_api_admin(&user)?;
+    let key = state.public_api.create_key(user.id, payload).await?;
+    tracing::info!(key_id = %key.key.id, user_id = %user.id, "Public API key created");
+    Ok(created(key))
+}
+
+async fn get_public_api_key_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_public_api_admin(&user)?;
+    Ok(json(state.public_api.get_key(id).await?))
+}
+
+async fn update_public_api_key_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+    Json(payload): Json<PublicApiKeyInput>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_public_api_admin(&user)?;
+    Ok(json(state.public_api.update_key(id, payload).await?))
+}
+
+/// Revoke a key; its usage stays in the reports
+async fn revoke_public_api_key_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_public_api_admin(&user)?;
+    let key = state.public_api.revoke_key(id).await?;
+    tracing::info!(key_id = %key.id, user_id = %user.id, "Public API key revoked");
+    Ok(json(key))
+}
+
+/// Usage report query parameters
+#[derive(Debug, Deserialize)]
+struct PublicApiUsageQuery {
+    #[serde(default = "default_usage_days")]
+    days: u32,
+    key_id: Option<Uuid>,
+}
+
+fn default_usage_days() -> u32 {
+    30
+}
+
+/// Requests, bandwidth and top endpoints per public API key
+async fn public_api_usage_handler(
+    user: AuthUser,
+    Query(query): Query<PublicApiUsageQuery>,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_public_api_admin(&user)?;
+    Ok(json(
+        state
+            .public_api
+            .usage_report(query.days, query.key_id)
+            .await?,
+    ))
+}
+
+// =============================================================================
+// HTTP Signature Routes and Handlers
+// =============================================================================
+
+use crate::extract::SignedRequest;
+use crate::services::{InboundKeyInput, KeyDirection, OutboundKeyInput};
+
+/// HTTP message signature key management
+fn http_signature_routes() -> Router<AppState> {
+    Router::new()
+        .route("/keys", get(list_signature_keys_handler))
+        .route("/keys/inbound", post(register_inbound_key_handler))
+        .route("/keys/outbound", post(create_outbound_key_handler))
+        .route(
+            "/keys/:id",
+            get(get_signature_key_handler).delete(revoke_signature_key_handler),
+        )
+        .route(
+            "/verify",
+            post(verify_signature_handler).get(verify_signature_handler),
+        )
+}
+
+fn require_signature_admin(user: &AuthUser) -> HttpResult<()> {
+    if user.is_admin() {
+        Ok(())
+    } else {
+        Err(HttpError::forbidden(
+            "Only administrators can manage signature keys",
+        ))
+    }
+}
+
+/// Signature key list filter
+#[derive(Debug, Deserialize)]
+struct SignatureKeyQuery {
+    direction: Option<KeyDirection>,
+}
+
+async fn list_signature_keys_handler(
+    user: AuthUser,
+    Query(query): Query<SignatureKeyQuery>,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_signature_admin(&user)?;
+    Ok(json(
+        state.http_signatures.list_keys(query.direction).await?,
+    ))
+}
+
+/// Trust the public key of an integration
+async fn register_inbound_key_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(payload): Json<InboundKeyInput>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_signature_admin(&user)?;
+    let key = state
+        .http_signatures
+        .register_inbound_key(user.id, payload)
+        .await?;
+    tracing::info!(key_id = %key.key_id, user_id = %user.id, "Inbound signature key registered");
+    Ok(created(key))
+}
+
+/// Generate a key for signing outbound requests; the newest one is used
+async fn create_outbound_key_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(payload): Json<OutboundKeyInput>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_signature_admin(&user)?;
+    let key = state
+        .http_signatures
+        .create_outbound_key(user.id, payload)
+        .await?;
+    tracing::info!(key_id = %key.key_id, user_id = %user.id, "Outbound signature key created");
+    Ok(created(key))
+}
+
+async fn get_signature_key_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_signature_admin(&user)?;
+    Ok(json(state.http_signatures.get_key(id).await?))
+}
+
+async fn revoke_signature_key_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_signature_admin(&user)?;
+    let key = state.http_signatures.revoke_key(id).await?;
+    tracing::info!(key_id = %key.key_id, user_id = %user.id, "Signature key revoked");
+    Ok(json(key))
+}
+
+/// Echo a verified signature, so integrations can test their signing
+async fn verify_signature_handler(
+    SignedRequest(signature): SignedRequest,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    Ok(json(signature))
+}
+
+/// JWK set of the keys outbound requests are signed with
+async fn signature_directory_handler(
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let directory = state.http_signatures.signature_directory().await?;
+    Ok((
+        [
+            (
+                header::CONTENT_TYPE,
+                crate::services::http_signatures::SIGNATURE_DIRECTORY_CONTENT_TYPE,
+            ),
+            (header::CACHE_CONTROL, "public, max-age=300"),
+        ],
+        Json(directory),
+    ))
+}
+
+// =============================================================================
+// Email Routes and Handlers
+// =============================================================================
+// Webhook Routes and Handlers
+// =============================================================================
+
+use crate::services::{
+    DeliveryQuery, WebhookEndpoint, WebhookEndpointInput, WebhookEndpointUpdate,
+};
+
+/// Outbound webhook endpoints, their deliveries and redelivery
+fn webhook_routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_webhooks_handler).post(create_webhook_handler))
+        .route(
+            "/:id",
+            get(get_webhook_handler)
+                .put(update_webhook_handler)
+                .delete(delete_webhook_handler),
+        )
+        .route("/:id/secret", post(rotate_webhook_secret_handler))
+        .route("/:id/ping", post(ping_webhook_handler))
+        .route("/:id/deliveries", get(list_webhook_deliveries_handler))
+        .route("/deliveries/:id", get(get_webhook_delivery_handler))
+        .route("/deliveries/:id/redeliver", post(redeliver_webhook_handler))
+}
+
+/// Administrators and users granted `webhooks:manage` register webhooks
+async fn require_webhook_access(user: &AuthUser, state: &AppState) -> HttpResult<()> {
+    if user.is_admin() {
+        return Ok(());
+    }
+    require_permission(user, state, "webhooks", "manage").await
+}
+
+/// An endpoint the user may manage; others' endpoints are only visible to
+/// administrators
+async fn owned_webhook(user: &AuthUser, state: &AppState, id: Uuid) -> HttpResult<WebhookEndpoint> {
+    require_webhook_access(user, state).await?;
+    let endpoint = state.webhooks.get(id).await?;
+    if endpoint.owner_id != user.id && !user.is_admin() {
+        return Err(HttpError::not_found("Webhook endpoint not found"));
+    }
+    Ok(endpoint)
+}
+
+async fn list_webhooks_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_webhook_access(&user, &state).await?;
+    let owner = (!user.is_admin()).then_some(user.id);
+    Ok(json(state.webhooks.list(owner).await?))
+}
+
+/// Register an endpoint; its signing secret is only returned here
+async fn create_webhook_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(payload): Json<WebhookEndpointInput>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_webhook_access(&user, &state).await?;
+    let endpoint = state.webhooks.create(user.id, payload).await?;
+    tracing::info!(endpoint_id = %endpoint.endpoint.id, user_id = %user.id, "Webhook endpoint registered");
+    Ok(created(endpoint))
+}
+
+async fn get_webhook_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    Ok(json(owned_webhook(&user, &state, id).await?))
+}
+
+async fn update_webhook_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+    Json(payload): Json<WebhookEndpointUpdate>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    owned_webhook(&user, &state, id).await?;
+    Ok(json(state.webhooks.update(id, payload).await?))
+}
+
+async fn delete_webhook_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    owned_webhook(&user, &state, id).await?;
+    state.webhooks.delete(id).await?;
+    tracing::info!(endpoint_id = %id, user_id = %user.id, "Webhook endpoint deleted");
+    Ok(no_content())
+}
+
+/// Replace an endpoint's signing secret, returning the new one
+async fn rotate_webhook_secret_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    owned_webhook(&user, &state, id).await?;
+    let endpoint = state.webhooks.rotate_secret(id).await?;
+    tracing::info!(endpoint_id = %id, user_id = %user.id, "Webhook secret rotated");
+    Ok(json(endpoint))
+}
+
+/// Queue a `webhook.ping` delivery to test an endpoint
+async fn ping_webhook_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    owned_webhook(&user, &state, id).await?;
+    let delivery = state.webhooks.ping(id).await?;
+    let status_url = format!("/api/v1/webhooks/deliveries/{}", delivery.id);
+    Ok(Accepted::new("Ping queued")
+        .with_data(delivery)
+        .with_status_url(status_url))
+}
+
+/// Newest deliveries to an endpoint, optionally of one status
+async fn list_webhook_deliveries_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    Query(query): Query<DeliveryQuery>,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    owned_webhook(&user, &state, id).await?;
+    Ok(json(state.webhooks.deliveries(id, &query).await?))
+}
+
+/// A delivery with the log of its attempts
+async fn get_webhook_delivery_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_webhook_access(&user, &state).await?;
+    let detail = state.webhooks.delivery_detail(id).await?;
+    owned_webhook(&user, &state, detail.delivery.endpoint_id).await?;
+    Ok(json(detail))
+}
+
+/// Send a delivery again with the same body
+async fn redeliver_webhook_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_webhook_access(&user, &state).await?;
+    let original = state.webhooks.delivery(id).await?;
+    owned_webhook(&user, &state, original.endpoint_id).await?;
+    let delivery = state.webhooks.redeliver(id).await?;
+    tracing::info!(delivery_id = %id, redelivery_id = %delivery.id, user_id = %user.id, "Webhook redelivered");
+    let status_url = format!("/api/v1/webhooks/deliveries/{}", delivery.id);
+    Ok(Accepted::new("Redelivery queued")
+        .with_data(delivery)
+        .with_status_url(status_url))
+}
+
+use crate::services::{
+    InboundMailbox, InboundMailboxInput, InboundMailboxUpdate, InboundMessageQuery, MailboxKind,
+};
+
+/// Inbound mailboxes of the current site and the messages they received
+fn inbound_email_routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/mailboxes",
+            get(list_inbound_mailboxes_handler).post(create_inbound_mailbox_handler),
+        )
+        .route(
+            "/mailboxes/:id",
+            get(get_inbound_mailbox_handler)
+                .put(update_inbound_mailbox_handler)
+                .delete(delete_inbound_mailbox_handler),
+        )
+        .route(
+            "/mailboxes/:id/token",
+            post(rotate_inbound_mailbox_token_handler),
+        )
+        .route("/mailboxes/:id/poll", post(poll_inbound_mailbox_handler))
+        .route(
+            "/mailboxes/:id/messages",
+            get(list_inbound_messages_handler),
+        )
+}
+
+/// Administrators and users granted `inbound_email:manage` manage mailboxes;
+/// they hold mail server logins
+async fn require_inbound_email_access(user: &AuthUser, state: &AppState) -> HttpResult<()> {
+    if user.is_admin() {
+        return Ok(());
+    }
+    require_permission(user, state, "inbound_email", "manage").await
+}
+
+async fn inbound_mailbox(
+    user: &AuthUser,
+    state: &AppState,
+    id: Uuid,
+) -> HttpResult<InboundMailbox> {
+    require_inbound_email_access(user, state).await?;
+    Ok(state.inbound_email.get(id).await?)
+}
+
+async fn list_inbound_mailboxes_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_inbound_email_access(&user, &state).await?;
+    Ok(json(state.inbound_email.list().await?))
+}
+
+/// Add a mailbox; Mailgun and SES deliver to the URL made with its token
+async fn create_inbound_mailbox_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(payload): Json<InboundMailboxInput>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_inbound_email_access(&user, &state).await?;
+    let mailbox = state.inbound_email.create(user.id, payload).await?;
+    tracing::info!(mailbox_id = %mailbox.id, user_id = %user.id, "Inbound mailbox added");
+    Ok(created(mailbox))
+}
+
+async fn get_inbound_mailbox_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    Ok(json(inbound_mailbox(&user, &state, id).await?))
+}
+
+async fn update_inbound_mailbox_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+    Json(payload): Json<InboundMailboxUpdate>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    inbound_mailbox(&user, &state, id).await?;
+    Ok(json(state.inbound_email.update(id, payload).await?))
+}
+
+async fn delete_inbound_mailbox_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    inbound_mailbox(&user, &state, id).await?;
+    state.inbound_email.delete(id).await?;
+    tracing::info!(mailbox_id = %id, user_id = %user.id, "Inbound mailbox deleted");
+    Ok(no_content())
+}
+
+/// Replace a mailbox's webhook token after it leaked
+async fn rotate_inbound_mailbox_token_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    inbound_mailbox(&user, &state, id).await?;
+    let mailbox = state.inbound_email.rotate_token(id).await?;
+    tracing::info!(mailbox_id = %id, user_id = %user.id, "Inbound mailbox token rotated");
+    Ok(json(mailbox))
+}
+
+/// Fetch new mail from an IMAP mailbox now instead of at the next poll
+async fn poll_inbound_mailbox_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let mailbox = inbound_mailbox(&user, &state, id).await?;
+    if mailbox.kind != MailboxKind::Imap.as_str() {
+        return Err(HttpError::bad_request("Only IMAP mailboxes are polled"));
+    }
+    let received = state.inbound_email.poll(&mailbox).await?;
+    Ok(json(serde_json::json!({ "received": received })))
+}
+
+/// Newest messages of a mailbox, optionally of one status
+async fn list_inbound_messages_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    Query(query): Query<InboundMessageQuery>,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    inbound_mailbox(&user, &state, id).await?;
+    Ok(json(state.inbound_email.messages(id, &query).await?))
+}
+
+// =============================================================================
+
+/// Email routes for SMTP configuration and testing
+fn email_routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/config",
+            get(email_config_handler).put(email_config_update_handler),
+        )
+        .route(
+            "/settings",
+            get(email_config_handler).put(email_config_update_handler),
+        )
+        .route("/test", post(email_test_handler))
+        .route("/templates", get(email_templates_handler))
+        .route("/send", post(email_send_handler))
+}
+
+/// Email configuration response (sanitized)
+#[derive(Debug, Serialize, Deserialize)]
+struct EmailConfigResponse {
+    smtp_host: String,
+    smtp_port: u16,
+    smtp_username: Option<String>,
+    smtp_tls: bool,
+    from_email: String,
+    from_name: String,
+    site_name: String,
+    site_url: String,
+    enabled: bool,
+}
+
+/// Email configuration update request
+#[derive(Debug, Deserialize)]
+struct EmailConfigUpdate {
+    smtp_host: Option<String>,
+    smtp_port: Option<u16>,
+    smtp_username: Option<String>,
+    smtp_password: Option<String>,
+    smtp_tls: Option<bool>,
+    from_email: Option<String>,
+    from_name: Option<String>,
+    site_name: Option<String>,
+    site_url: Option<String>,
+    enabled: Option<bool>,
+}
+
+/// Get email configuration
+async fn email_config_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    // Only admins can view email config
+    if user.claims.role.as_deref() != Some("administrator") {
+        return Err(rustpress_core::error::Error::authorization(
+            "view email configuration",
+            "administrator",
+        )
+        .into());
+    }
+
+    let pool = state.db().inner();
+
+    // Get email settings from options table
+    let settings: Option<(serde_json::Value,)> =
+        sqlx::query_as("SELECT option_value FROM options WHERE option_name = 'email_settings'")
+            .fetch_optional(pool)
+            .await
+            .unwrap_or(None);
+
+    let config = if let Some((value,)) = settings {
+        serde_json::from_value::<EmailConfigResponse>(value).unwrap_or_else(|_| {
+            EmailConfigResponse {
+                smtp_host: "localhost".to_string(),
+                smtp_port: 587,
+                smtp_username: None,
+                smtp_tls: true,
+                from_email: "noreply@localhost".to_string(),
+                from_name: "RustPress".to_string(),
+                site_name: "RustPress".to_string(),
+                site_url: "http://localhost".to_string(),
+                enabled: false,
+            }
+        })
+    } else {
+        EmailConfigResponse {
+            smtp_host: "localhost".to_string(),
+            smtp_port: 587,
+            smtp_username: None,
+            smtp_tls: true,
+            from_email: "noreply@localhost".to_string(),
+            from_name: "RustPress".to_string(),
+            site_name: "RustPress".to_string(),
+            site_url: "http://localhost".to_string(),
+            enabled: false,
+        }
+    };
+
+    Ok(json(config))
+}
+
+/// Update email configuration
+async fn email_config_update_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(payload): Json<EmailConfigUpdate>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    // Only admins can update email config
+    if user.claims.role.as_deref() != Some("administrator") {
+        return Err(rustpress_core::error::Error::authorization(
+            "update email configuration",
+            "administrator",
+        )
+        .into());
+    }
+
+    let pool = state.db().inner();
+
+    // Get current settings
+    let current: Option<(serde_json::Value,)> =
+        sqlx::query_as("SELECT option_value FROM options WHERE option_name = 'email_settings'")
+            .fetch_optional(pool)
+            .await
+            .unwrap_or(None);
+
+    // Build updated config (merge with existing)
+    let mut config = if let Some((value,)) = current {
+        value
+    } else {
+        serde_json::json!({
+            "smtp_host": "localhost",
+            "smtp_port": 587,
+            "smtp_tls": true,
+            "from_email": "noreply@localhost",
+            "from_name": "RustPress",
+            "site_name": "RustPress",
+            "site_url": "http://localhost",
+            "enabled": false
+        })
+    };
+
+    // Update fields if provided
+    if let Some(smtp_host) = payload.smtp_host {
+        config["smtp_host"] = serde_json::json!(smtp_host);
+    }
+    if let Some(smtp_port) = payload.smtp_port {
+        config["smtp_port"] = serde_json::json!(smtp_port);
+    }
+    if let Some(smtp_username) = payload.smtp_username {
+        config["smtp_username"] = serde_json::json!(smtp_username);
+    }
+    if let Some(smtp_password) = payload.smtp_password {
+        config["smtp_password"] = serde_json::json!(smtp_password);
+    }
+    if let Some(smtp_tls) = payload.smtp_tls {
+        config["smtp_tls"] = serde_json::json!(smtp_tls);
+    }
+    if let Some(from_email) = payload.from_email {
+        config["from_email"] = serde_json::json!(from_email);
+    }
+    if let Some(from_name) = payload.from_name {
+        config["from_name"] = serde_json::json!(from_name);
+    }
+    if let Some(site_name) = payload.site_name {
+        config["site_name"] = serde_json::json!(site_name);
+    }
+    if let Some(site_url) = payload.site_url {
+        config["site_url"] = serde_json::json!(site_url);
+    }
+    if let Some(enabled) = payload.enabled {
+        config["enabled"] = serde_json::json!(enabled);
+    }
+
+    // Save to database (upsert)
+    sqlx::query(
+        r#"
+        INSERT INTO options (option_name, option_value, autoload)
+        VALUES ('email_settings', $1, true)
+        ON CONFLICT (option_name) DO UPDATE SET option_value = $1
+        "#,
+    )
+    .bind(&config)
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        rustpress_core::error::Error::database_with_source("Failed to save email settings", e)
+    })?;
+
+    // Configure the email service with new settings if it exists in AppState
+    // Note: This would require adding EmailService to AppState
+
+    Ok(json(serde_json::json!({
+        "success": true,
+        "message": "Email configuration updated"
+    })))
+}
+
+/// Test email request
+#[derive(Debug, Deserialize)]
+struct EmailTestRequest {
+    to_email: String,
+}
+
+/// Send test email
+async fn email_test_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(payload): Json<EmailTestRequest>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    // Only admins can send test emails
+    if user.claims.role.as_deref() != Some("administrator") {
+        return Err(rustpress_core::error::Error::authorization(
+            "send test emails",
+            "administrator",
+        )
+        .into());
+    }
+
+    let pool = state.db().inner();
+
+    // Get email settings
+    let settings: Option<(serde_json::Value,)> =
+        sqlx::query_as("SELECT option_value FROM options WHERE option_name = 'email_settings'")
+            .fetch_optional(pool)
+            .await
+            .unwrap_or(None);
+
+    let config = if let Some((value,)) = settings {
+        value
+    } else {
+        return Ok(json(serde_json::json!({
+            "success": false,
+            "error": "Email not configured. Please configure SMTP settings first."
+        })));
+    };
+
+    let enabled = config
+        .get("enabled")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    if !enabled {
+        return Ok(json(serde_json::json!({
+            "success": false,
+            "error": "Email service is disabled. Enable it in settings first."
+        })));
+    }
+
+    // Create email service and send test
+    use crate::services::email_service::{EmailConfig, EmailService};
+
+    let email_config = EmailConfig {
+        smtp_host: config
+            .get("smtp_host")
+            .and_then(|v| v.as_str())
+            .unwrap_or("localhost")
+            .to_string(),
+        smtp_port: config
+            .get("smtp_port")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(587) as u16,
+        smtp_username: config
+            .get("smtp_username")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string()),
+        smtp_password: config
+            .get("smtp_password")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string()),
+        smtp_tls: config
+            .get("smtp_tls")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true),
+        from_email: config
+            .get("from_email")
+            .and_then(|v| v.as_str())
+            .unwrap_or("noreply@localhost")
+            .to_string(),
+        from_name: config
+            .get("from_name")
+            .and_then(|v| v.as_str())
+            .unwrap_or("RustPress")
+            .to_string(),
+        site_name: config
+            .get("site_name")
+            .and_then(|v| v.as_str())
+            .unwrap_or("RustPress")
+            .to_string(),
+        site_url: config
+            .get("site_url")
+            .and_then(|v| v.as_str())
+            .unwrap_or("http://localhost")
+            .to_string(),
+        enabled: true,
+    };
+
+    let service = EmailService::new();
+    if let Err(e) = service.configure(email_config).await {
+        return Ok(json(serde_json::json!({
+            "success": false,
+            "error": format!("Failed to configure email service: {}", e)
+        })));
+    }
+
+    match service.send_test(&payload.to_email).await {
+        Ok(result) => {
+            if result.success {
+                Ok(json(serde_json::json!({
+                    "success": true,
+                    "message": format!("Test email sent successfully to {}", payload.to_email),
+                    "message_id": result.message_id
+                })))
+            } else {
+                Ok(json(serde_json::json!({
+                    "success": false,
+                    "error": result.error.unwrap_or_else(|| "Unknown error".to_string())
+                })))
+            }
+        }
+        Err(e) => Ok(json(serde_json::json!({
+            "success": false,
+            "error": format!("{}", e)
+        }))),
+    }
+}
+
+/// List available email templates
+async fn email_templates_handler(
+    user: AuthUser,
+    State(_state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    // Only admins can view templates
+    if user.claims.role.as_deref() != Some("administrator") {
+        return Err(rustpress_core::error::Error::authorization(
+            "view email templates",
+            "administrator",
+        )
+        .into());
+    }
+
+    let templates = vec![
+        serde_json::json!({
+            "id": "password_reset",
+            "name": "Password Reset",
+            "description": "Sent when a user requests a password reset",
+            "variables": ["name", "reset_url", "expires_hours"]
+        }),
+        serde_json::json!({
+            "id": "email_verification",
+            "name": "Email Verification",
+            "description": "Sent to verify a user's email address",
+            "variables": ["name", "verify_url"]
+        }),
+        serde_json::json!({
+            "id": "welcome",
+            "name": "Welcome Email",
+            "description": "Sent when a new user registers",
+            "variables": ["name", "login_url"]
+        }),
+        serde_json::json!({
+            "id": "new_comment",
+            "name": "New Comment Notification",
+            "description": "Sent when a new comment is posted",
+            "variables": ["author_name", "post_title", "comment_content"]
+        }),
+        serde_json::json!({
+            "id": "comment_approved",
+            "name": "Comment Approved",
+            "description": "Sent when a comment is approved",
+            "variables": ["name", "post_title"]
+        }),
+        serde_json::json!({
+            "id": "post_published",
+            "name": "Post Published",
+            "description": "Sent when a post is published",
+            "variables": ["author_name", "post_title", "post_url"]
+        }),
+        serde_json::json!({
+            "id": "account_deactivated",
+            "name": "Account Deactivated",
+            "description": "Sent when an account is deactivated",
+            "variables": ["name"]
+        }),
+        serde_json::json!({
+            "id": "security_alert",
+            "name": "Security Alert",
+            "description": "Sent for security-related notifications",
+            "variables": ["name", "alert_type", "alert_details"]
+        }),
+    ];
+
+    Ok(json(serde_json::json!({
+        "templates": templates
+    })))
+}
+
+/// Send email request
+#[derive(Debug, Deserialize)]
+struct SendEmailRequest {
+    template: String,
+    to_email: String,
+    to_name: Option<String>,
+    variables: Option<std::collections::HashMap<String, serde_json::Value>>,
+}
+
+/// Send an email using a template
+async fn email_send_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(payload): Json<SendEmailRequest>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    // Only admins can send emails
+    if user.claims.role.as_deref() != Some("administrator") {
+        return Err(
+            rustpress_core::error::Error::authorization("send emails", "administrator").into(),
+        );
+    }
+
+    let pool = state.db().inner();
+
+    // Get email settings
+    let settings: Option<(serde_json::Value,)> =
+        sqlx::query_as("SELECT option_value FROM options WHERE option_name = 'email_settings'")
+            .fetch_optional(pool)
+            .await
+            .unwrap_or(None);
+
+    let config = if let Some((value,)) = settings {
+        value
+    } else {
+        return Ok(json(serde_json::json!({
+            "success": false,
+            "error": "Email not configured"
+        })));
+    };
+
+    let enabled = config
+        .get("enabled")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    if !enabled {
+        return Ok(json(serde_json::json!({
+            "success": false,
+            "error": "Email service is disabled"
+        })));
+    }
+
+    // Map template string to EmailTemplate enum
+    use crate::services::email_service::{EmailConfig, EmailService, EmailTemplate};
+
+    let template = match payload.template.as_str() {
+        "password_reset" => EmailTemplate::PasswordReset,
+        "email_verification" => EmailTemplate::EmailVerification,
+        "welcome" => EmailTemplate::Welcome,
+        "new_comment" => EmailTemplate::NewComment,
+        "comment_approved" => EmailTemplate::CommentApproved,
+        "post_published" => EmailTemplate::PostPublished,
+        "account_deactivated" => EmailTemplate::AccountDeactivated,
+        "security_alert" => EmailTemplate::SecurityAlert,
+        "invitation" => EmailTemplate::Invitation,
+        "group_notification" => EmailTemplate::GroupNotification,
+        _ => {
+            return Ok(json(serde_json::json!({
+                "success": false,
+                "error": format!("Unknown template: {}", payload.template)
+            })));
+        }
+    };
+
+    let email_config = EmailConfig {
+        smtp_host: config
+            .get("smtp_host")
+            .and_then(|v| v.as_str())
+            .unwrap_or("localhost")
+            .to_string(),
+        smtp_port: config
+            .get("smtp_port")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(587) as u16,
+        smtp_username: config
+            .get("smtp_username")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string()),
+        smtp_password: config
+            .get("smtp_password")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string()),
+        smtp_tls: config
+            .get("smtp_tls")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true),
+        from_email: config
+            .get("from_email")
+            .and_then(|v| v.as_str())
+            .unwrap_or("noreply@localhost")
+            .to_string(),
+        from_name: config
+            .get("from_name")
+            .and_then(|v| v.as_str())
+            .unwrap_or("RustPress")
+            .to_string(),
+        site_name: config
+            .get("site_name")
+            .and_then(|v| v.as_str())
+            .unwrap_or("RustPress")
+            .to_string(),
+        site_url: config
+            .get("site_url")
+            .and_then(|v| v.as_str())
+            .unwrap_or("http://localhost")
+            .to_string(),
+        enabled: true,
+    };
+
+    let service = EmailService::new();
+    if let Err(e) = service.configure(email_config).await {
+        return Ok(json(serde_json::json!({
+            "success": false,
+            "error": format!("Failed to configure email service: {}", e)
+        })));
+    }
+
+    let variables = payload.variables.unwrap_or_default();
+
+    match service
+        .send_template(
+            template,
+            &payload.to_email,
+            payload.to_name.as_deref(),
+            variables,
+        )
+        .await
+    {
+        Ok(result) => {
+            if result.success {
+                Ok(json(serde_json::json!({
+                    "success": true,
+                    "message": format!("Email sent to {}", payload.to_email),
+                    "message_id": result.message_id
+                })))
+            } else {
+                Ok(json(serde_json::json!({
+                    "success": false,
+                    "error": result.error.unwrap_or_else(|| "Unknown error".to_string())
+                })))
+            }
+        }
+        Err(e) => Ok(json(serde_json::json!({
+            "success": false,
+            "error": format!("{}", e)
+        }))),
+    }
+}
+
+/// Cloudflare plugin routes builder
+/// This returns a router with CloudflareServices state that will be merged at the api_v1 level
+pub fn build_cloudflare_router(state: &AppState) -> Router {
+    use rustcloudflare::services::CloudflareServices;
+
+    // Get the database pool from AppState
+    let db_pool = state.database.inner().clone();
+
+    // Create unconfigured cloudflare services (config loaded dynamically from DB)
+    let services = Arc::new(CloudflareServices::new_unconfigured(db_pool));
+
+    // Create the cloudflare router with its own state
+    rustcloudflare::api::create_router(services)
+}
+
+/// RustBuilder page builder plugin routes builder
+/// This returns a router with RustBuilder's own state for the visual page builder
+pub fn build_rustbuilder_router(state: &AppState) -> Router {
+    // Get the database pool from AppState
+    let db_pool = state.database.inner().clone();
+
+    // Create the rustbuilder router with its own state
+    rustbuilder::api::create_router(db_pool)
+}
+
+// ============================================
+// Chat Handlers
+// ============================================
+
+/// Chat pagination query params
+#[derive(Debug, Deserialize)]
+struct ChatPaginationQuery {
+    #[serde(default = "default_chat_limit")]
+    limit: i64,
+    #[serde(default)]
+    offset: i64,
+}
+
+fn default_chat_limit() -> i64 {
+    50
+}
+
+/// Get or create personal notes conversation (messages to self)
+async fn personal_notes_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let pool = state.db().inner();
+
+    // Check if personal notes conversation already exists
+    let existing: Option<(Uuid, Option<String>, String)> = sqlx::query_as(
+        r#"
+        SELECT c.id, c.title, c.type
+        FROM chat_conversations c
+        JOIN chat_conversation_participants p ON p.conversation_id = c.id
+        WHERE c.type = 'personal' AND c.created_by = $1 AND p.user_id = $1
+        AND (SELECT COUNT(*) FROM chat_conversation_participants WHERE conversation_id = c.id) = 1
+        LIMIT 1
+        "#,
+    )
+    .bind(user.id)
+    .fetch_optional(pool)
+    .await
+    .unwrap_or(None);
+
+    if let Some((id, title, conv_type)) = existing {
+        return Ok(json(serde_json::json!({
+            "id": id,
+            "title": title.unwrap_or_else(|| "Personal Notes".to_string()),
+            "type": conv_type,
+            "created": false
+        })));
+    }
+
+    // Create new personal notes conversation
+    let conv_id: Uuid = sqlx::query_scalar(
+        r#"
+        INSERT INTO chat_conversations (title, type, created_by)
+        VALUES ('Personal Notes', 'personal', $1)
+        RETURNING id
+        "#,
+    )
+    .bind(user.id)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        rustpress_core::error::Error::internal(format!("Failed to create personal notes: {}", e))
+    })?;
+
+    // Add user as the only participant
+    sqlx::query("INSERT INTO chat_conversation_participants (conversation_id, user_id, role) VALUES ($1, $2, 'admin')")
+        .bind(conv_id)
+        .bind(user.id)
+        .execute(pool)
+        .await
+        .ok();
+
+    Ok(json(serde_json::json!({
+        "id": conv_id,
+        "title": "Personal Notes",
+        "type": "personal",
+        "created": true
+    })))
+}
+
+/// Get online users for creating group chats
+async fn online_users_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let online_users = state.ws_hub().get_online_users().await;
+
+    // Filter out current user and map to response format
+    let users: Vec<serde_json::Value> = online_users
+        .into_iter()
+        .filter(|u| u.user_id != user.id)
+        .map(|u| {
+            serde_json::json!({
+                "id": u.user_id,
+                "username": u.username,
+                "display_name": u.display_name,
+                "avatar_url": u.avatar_url,
+                "status": u.status,
+                "color": u.color,
+                "current_file": u.current_file
+            })
+        })
+        .collect();
+
+    Ok(json(serde_json::json!({
+        "users": users,
+        "count": users.len()
+    })))
+}
+
+/// Create group chat request
+#[derive(Debug, Deserialize)]
+struct CreateGroupChatRequest {
+    title: String,
+    participant_ids: Vec<Uuid>,
+}
+
+/// Create a new group chat with online users
+async fn create_group_chat_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(payload): Json<CreateGroupChatRequest>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let pool = state.db().inner();
+
+    if payload.participant_ids.is_empty() {
+        return Err(rustpress_core::error::Error::validation(
+            "At least one participant is required",
+        )
+        .into());
+    }
+
+    // Create group conversation
+    let conv_id: Uuid = sqlx::query_scalar(
+        r#"
+        INSERT INTO chat_conversations (title, type, created_by)
+        VALUES ($1, 'group', $2)
+        RETURNING id
+        "#,
+    )
+    .bind(&payload.title)
+    .bind(user.id)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        rustpress_core::error::Error::internal(format!("Failed to create group chat: {}", e))
+    })?;
+
+    // Add creator as admin
+    sqlx::query("INSERT INTO chat_conversation_participants (conversation_id, user_id, role) VALUES ($1, $2, 'admin')")
+        .bind(conv_id)
+        .bind(user.id)
+        .execute(pool)
+        .await
+        .ok();
+
+    // Add other participants
+    for participant_id in &payload.participant_ids {
+        sqlx::query("INSERT INTO chat_conversation_participants (conversation_id, user_id, role) VALUES ($1, $2, 'member')")
+            .bind(conv_id)
+            .bind(participant_id)
+            .execute(pool)
+            .await
+            .ok();
+    }
+
+    // Notify participants via WebSocket
+    let ws_hub = state.ws_hub();
+    for participant_id in &payload.participant_ids {
+        ws_hub
+            .send_to_user(
+                *participant_id,
+                crate::websocket::ServerMessage::ChatMessage {
+                    message: crate::websocket::message::ChatMessageDto {
+                        id: uuid::Uuid::new_v4(),
+                        conversation_id: conv_id,
+                        sender_id: user.id,
+                        sender_name: "System".to_string(),
+                        sender_avatar: None,
+                        content: format!("You have been added to group chat: {}", payload.title),
+                        content_type: "system".to_string(),
+                        reply_to_id: None,
+                        is_pinned: false,
+                        is_edited: false,
+                        reactions: vec![],
+                        created_at: chrono::Utc::now(),
+                    },
+                },
+            )
+            .await;
+    }
+
+    Ok(created(serde_json::json!({
+        "id": conv_id,
+        "title": payload.title,
+        "type": "group",
+        "participants": payload.participant_ids.len() + 1
+    })))
+}
+
+/// List conversations for the current user
+async fn list_conversations_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Query(params): Query<ChatPaginationQuery>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let pool = state.db().inner();
+
+    let conversations: Vec<serde_json::Value> =
+        sqlx::query_as::<_, (Uuid, Option<String>, String, chrono::DateTime<chrono::Utc>)>(
+            r#"
+        SELECT c.id, c.title, c.type, c.updated_at
+        FROM chat_conversations c
+        JOIN chat_conversation_participants p ON p.conversation_id = c.id
+        WHERE p.user_id = $1 AND c.is_archived = false
+        ORDER BY c.updated_at DESC
+        LIMIT $2 OFFSET $3
+        "#,
+        )
+        .bind(user.id)
+        .bind(params.limit)
+        .bind(params.offset)
+        .fetch_all(pool)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(id, title, conv_type, updated_at)| {
+            serde_json::json!({
+                "id": id,
+                "title": title,
+                "type": conv_type,
+                "updated_at": updated_at
+            })
+        })
+        .collect();
+
+    Ok(json(serde_json::json!({ "conversations": conversations })))
+}
+
+/// Create conversation request
+#[derive(Debug, Deserialize)]
+struct CreateConversationRequest {
+    title: Option<String>,
+    #[serde(rename = "type")]
+    conversation_type: Option<String>,
+    participant_ids: Vec<Uuid>,
+}
+
+/// Create a new conversation
+async fn create_conversation_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(payload): Json<CreateConversationRequest>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let pool = state.db().inner();
+    let conv_type = payload
+        .conversation_type
+        .unwrap_or_else(|| "direct".to_string());
+
+    // Create conversation
+    let conv_id: Uuid = sqlx::query_scalar(
+        r#"
+        INSERT INTO chat_conversations (title, type, created_by)
+        VALUES ($1, $2, $3)
+        RETURNING id
+        "#,
+    )
+    .bind(&payload.title)
+    .bind(&conv_type)
+    .bind(user.id)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        rustpress_core::error::Error::internal(format!("Failed to create conversation: {}", e))
+    })?;
+
+    // Add creator as participant
+    sqlx::query("INSERT INTO chat_conversation_participants (conversation_id, user_id, role) VALUES ($1, $2, 'admin')")
+        .bind(conv_id)
+        .bind(user.id)
+        .execute(pool)
+        .await
+        .ok();
+
+    // Add other participants
+    for participant_id in payload.participant_ids {
+        sqlx::query(
+            "INSERT INTO chat_conversation_participants (conversation_id, user_id) VALUES ($1, $2)",
+        )
+        .bind(conv_id)
+        .bind(participant_id)
+        .execute(pool)
+        .await
+        .ok();
+    }
+
+    Ok(created(serde_json::json!({
+        "id": conv_id,
+        "title": payload.title,
+        "type": conv_type
+    })))
+}
+
+/// Get a specific conversation
+async fn get_conversation_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let pool = state.db().inner();
+
+    let conv: Option<(Uuid, Option<String>, String, bool)> = sqlx::query_as(
+        r#"
+        SELECT c.id, c.title, c.type, c.is_archived
+        FROM chat_conversations c
+        JOIN chat_conversation_participants p ON p.conversation_id = c.id
+        WHERE c.id = $1 AND p.user_id = $2
+        "#,
+    )
+    .bind(id)
+    .bind(user.id)
+    .fetch_optional(pool)
+    .await
+    .unwrap_or(None);
+
+    match conv {
+        Some((id, title, conv_type, is_archived)) => Ok(json(serde_json::json!({
+            "id": id,
+            "title": title,
+            "type": conv_type,
+            "is_archived": is_archived
+        }))),
+        None => Err(rustpress_core::error::Error::not_found("Conversation", id.to_string()).into()),
+    }
+}
+
+/// Update conversation request
+#[derive(Debug, Deserialize)]
+struct UpdateConversationRequest {
+    title: Option<String>,
+}
+
+/// Update a conversation
+async fn update_conversation_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+    Json(payload): Json<UpdateConversationRequest>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let pool = state.db().inner();
+
+    sqlx::query("UPDATE chat_conversations SET title = COALESCE($1, title) WHERE id = $2")
+        .bind(&payload.title)
+        .bind(id)
+        .execute(pool)
+        .await
+        .map_err(|e| rustpress_core::error::Error::internal(format!("Failed to update: {}", e)))?;
+
+    Ok(json(serde_json::json!({ "success": true })))
+}
+
+/// Archive a conversation
+async fn archive_conversation_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let pool = state.db().inner();
+
+    sqlx::query("UPDATE chat_conversations SET is_archived = true WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await
+        .map_err(|e| rustpress_core::error::Error::internal(format!("Failed to archive: {}", e)))?;
+
+    Ok(no_content())
+}
+
+/// Get messages in a conversation
+async fn get_messages_handler(
+    _user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+    Query(params): Query<ChatPaginationQuery>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let pool = state.db().inner();
+
+    let messages: Vec<serde_json::Value> = sqlx::query_as::<
+        _,
+        (
+            Uuid,
+            Uuid,
+            String,
+            String,
+            bool,
+            bool,
+            chrono::DateTime<chrono::Utc>,
+        ),
+    >(
+        r#"
+        SELECT m.id, m.sender_id, m.content, m.content_type, m.is_pinned, m.is_edited, m.created_at
+        FROM chat_messages m
+        WHERE m.conversation_id = $1 AND m.deleted_at IS NULL
+        ORDER BY m.created_at DESC
+        LIMIT $2 OFFSET $3
+        "#,
+    )
+    .bind(id)
+    .bind(params.limit)
+    .bind(params.offset)
+    .fetch_all(pool)
+    .await
+    .unwrap_or_default()
+    .into_iter()
+    .map(
+        |(msg_id, sender_id, content, content_type, is_pinned, is_edited, created_at)| {
+            serde_json::json!({
+                "id": msg_id,
+                "sender_id": sender_id,
+                "content": content,
+                "content_type": content_type,
+                "is_pinned": is_pinned,
+                "is_edited": is_edited,
+                "created_at": created_at
+            })
+        },
+    )
+    .collect();
 
-    Ok(json(serde_json::json!({
-        "templates": templates
-    })))
+    Ok(json(serde_json::json!({ "messages": messages })))
 }
 
-/// Send email request
+/// Send message request
 #[derive(Debug, Deserialize)]
-struct SendEmailRequest {
-    template: String,
-    to_email: String,
-    to_name: Option<String>,
-    variables: Option<std::collections::HashMap<String, serde_json::Value>>,
+struct SendMessageRequest {
+    content: String,
+    content_type: Option<String>,
+    reply_to_id: Option<Uuid>,
 }
 
-/// Send an email using a template
-async fn email_send_handler(
+/// Send a message to a conversation
+async fn send_message_handler(
     user: AuthUser,
+    PathId(id): PathId,
     State(state): State<AppState>,
-    Json(payload): Json<SendEmailRequest>,
+    Json(payload): Json<SendMessageRequest>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    // Only admins can send emails
-    if user.claims.role.as_deref() != Some("administrator") {
-        return Err(
-            rustpress_core::error::Error::authorization("send emails", "administrator").into(),
-        );
-    }
-
     let pool = state.db().inner();
 
-    // Get email settings
-    let settings: Option<(serde_json::Value,)> =
-        sqlx::query_as("SELECT option_value FROM options WHERE option_name = 'email_settings'")
-            .fetch_optional(pool)
-            .await
-            .unwrap_or(None);
-
-    let config = if let Some((value,)) = settings {
-        value
-    } else {
-        return Ok(json(serde_json::json!({
-            "success": false,
-            "error": "Email not configured"
-        })));
-    };
-
-    let enabled = config
-        .get("enabled")
-        .and_then(|v| v.as_bool())
-        .unwrap_or(false);
-    if !enabled {
-        return Ok(json(serde_json::json!({
-            "success": false,
-            "error": "Email service is disabled"
-        })));
-    }
-
-    // Map template string to EmailTemplate enum
-    use crate::services::email_service::{EmailConfig, EmailService, EmailTemplate};
-
-    let template = match payload.template.as_str() {
-        "password_reset" => EmailTemplate::PasswordReset,
-        "email_verification" => EmailTemplate::EmailVerification,
-        "welcome" => EmailTemplate::Welcome,
-        "new_comment" => EmailTemplate::NewComment,
-        "comment_approved" => EmailTemplate::CommentApproved,
-        "post_published" => EmailTemplate::PostPublished,
-        "account_deactivated" => EmailTemplate::AccountDeactivated,
-        "security_alert" => EmailTemplate::SecurityAlert,
-        _ => {
-            return Ok(json(serde_json::json!({
-                "success": false,
-                "error": format!("Unknown template: {}", payload.template)
-            })));
-        }
-    };
-
-    let email_config = EmailConfig {
-        smtp_host: config
-            .get("smtp_host")
-            .and_then(|v| v.as_str())
-            .unwrap_or("localhost")
-            .to_string(),
-        smtp_port: config
-            .get("smtp_port")
-            .and_then(|v| v.as_u64())
-            .unwrap_or(587) as u16,
-        smtp_username: config
-            .get("smtp_username")
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string()),
-        smtp_password: config
-            .get("smtp_password")
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string()),
-        smtp_tls: config
-            .get("smtp_tls")
-            .and_then(|v| v.as_bool())
-            .unwrap_or(true),
-        from_email: config
-            .get("from_email")
-            .and_then(|v| v.as_str())
-            .unwrap_or("noreply@localhost")
-            .to_string(),
-        from_name: config
-            .get("from_name")
-            .and_then(|v| v.as_str())
-            .unwrap_or("RustPress")
-            .to_string(),
-        site_name: config
-            .get("site_name")
-            .and_then(|v| v.as_str())
-            .unwrap_or("RustPress")
-            .to_string(),
-        site_url: config
-            .get("site_url")
-            .and_then(|v| v.as_str())
-            .unwrap_or("http://localhost")
-            .to_string(),
-        enabled: true,
-    };
-
-    let service = EmailService::new();
-    if let Err(e) = service.configure(email_config).await {
-        return Ok(json(serde_json::json!({
-            "success": false,
-            "error": format!("Failed to configure email service: {}", e)
-        })));
-    }
-
-    let variables = payload.variables.unwrap_or_default();
+    let msg_id: Uuid = sqlx::query_scalar(
+        r#"
+        INSERT INTO chat_messages (conversation_id, sender_id, content, content_type, reply_to_id)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING id
+        "#,
+    )
+    .bind(id)
+    .bind(user.id)
+    .bind(&payload.content)
+    .bind(payload.content_type.as_deref().unwrap_or("text"))
+    .bind(payload.reply_to_id)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        rustpress_core::error::Error::internal(format!("Failed to send message: {}", e))
+    })?;
 
-    match service
-        .send_template(
-            template,
-            &payload.to_email,
-            payload.to_name.as_deref(),
-            variables,
-        )
+    // Broadcast via WebSocket
+    if let Ok(Some(dto)) = crate::websocket::chat::ChatService::new(pool.clone())
+        .get_message_dto(msg_id, user.id)
         .await
     {
-        Ok(result) => {
-            if result.success {
-                Ok(json(serde_json::json!({
-                    "success": true,
-                    "message": format!("Email sent to {}", payload.to_email),
-                    "message_id": result.message_id
-                })))
-            } else {
-                Ok(json(serde_json::json!({
-                    "success": false,
-                    "error": result.error.unwrap_or_else(|| "Unknown error".to_string())
-                })))
-            }
-        }
-        Err(e) => Ok(json(serde_json::json!({
-            "success": false,
-            "error": format!("{}", e)
-        }))),
+        state
+            .ws_hub
+            .broadcast_to_conversation(
+                id,
+                crate::websocket::ServerMessage::ChatMessage { message: dto },
+                None,
+            )
+            .await;
     }
-}
 
-/// Cloudflare plugin routes builder
-/// This returns a router with CloudflareServices state that will be merged at the api_v1 level
-pub fn build_cloudflare_router(state: &AppState) -> Router {
-    use rustcloudflare::services::CloudflareServices;
+    Ok(created(serde_json::json!({ "id": msg_id })))
+}
 
-    // Get the database pool from AppState
-    let db_pool = state.database.inner().clone();
+/// List participants in a conversation
+async fn list_participants_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let pool = state.db().inner();
 
-    // Create unconfigured cloudflare services (config loaded dynamically from DB)
-    let services = Arc::new(CloudflareServices::new_unconfigured(db_pool));
+    let participants: Vec<serde_json::Value> = sqlx::query_as::<_, (Uuid, String, Option<String>)>(
+        r#"
+        SELECT u.id, u.username, u.display_name
+        FROM chat_conversation_participants p
+        JOIN users u ON u.id = p.user_id
+        WHERE p.conversation_id = $1
+        "#,
+    )
+    .bind(id)
+    .fetch_all(pool)
+    .await
+    .unwrap_or_default()
+    .into_iter()
+    .map(|(uid, username, display_name)| {
+        serde_json::json!({
+            "id": uid,
+            "username": username,
+            "display_name": display_name
+        })
+    })
+    .collect();
 
-    // Create the cloudflare router with its own state
-    rustcloudflare::api::create_router(services)
+    Ok(json(serde_json::json!({ "participants": participants })))
 }
 
-/// RustBuilder page builder plugin routes builder
-/// This returns a router with RustBuilder's own state for the visual page builder
-pub fn build_rustbuilder_router(state: &AppState) -> Router {
-    // Get the database pool from AppState
-    let db_pool = state.database.inner().clone();
-
-    // Create the rustbuilder router with its own state
-    rustbuilder::api::create_router(db_pool)
+/// Add participant to conversation
+#[derive(Debug, Deserialize)]
+struct AddParticipantRequest {
+    user_id: Uuid,
 }
 
-// ============================================
-// Chat Handlers
-// ============================================
+async fn add_participant_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+    Json(payload): Json<AddParticipantRequest>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let pool = state.db().inner();
+
+    sqlx::query("INSERT INTO chat_conversation_participants (conversation_id, user_id) VALUES ($1, $2) ON CONFLICT DO NOTHING")
+        .bind(id)
+        .bind(payload.user_id)
+        .execute(pool)
+        .await
+        .ok();
 
-/// Chat pagination query params
-#[derive(Debug, Deserialize)]
-struct ChatPaginationQuery {
-    #[serde(default = "default_chat_limit")]
-    limit: i64,
-    #[serde(default)]
-    offset: i64,
+    Ok(created(serde_json::json!({ "success": true })))
 }
 
-fn default_chat_limit() -> i64 {
-    50
+/// Path parameters for participant removal
+#[derive(Debug, Deserialize)]
+struct ParticipantPath {
+    id: Uuid,
+    user_id: Uuid,
 }
 
-/// Get or create personal notes conversation (messages to self)
-async fn personal_notes_handler(
+async fn remove_participant_handler(
     user: AuthUser,
+    axum::extract::Path(params): axum::extract::Path<ParticipantPath>,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
     let pool = state.db().inner();
 
-    // Check if personal notes conversation already exists
-    let existing: Option<(Uuid, Option<String>, String)> = sqlx::query_as(
-        r#"
-        SELECT c.id, c.title, c.type
-        FROM chat_conversations c
-        JOIN chat_conversation_participants p ON p.conversation_id = c.id
-        WHERE c.type = 'personal' AND c.created_by = $1 AND p.user_id = $1
-        AND (SELECT COUNT(*) FROM chat_conversation_participants WHERE conversation_id = c.id) = 1
-        LIMIT 1
-        "#,
+    sqlx::query(
+        "DELETE FROM chat_conversation_participants WHERE conversation_id = $1 AND user_id = $2",
     )
-    .bind(user.id)
-    .fetch_optional(pool)
+    .bind(params.id)
+    .bind(params.user_id)
+    .execute(pool)
     .await
-    .unwrap_or(None);
+    .ok();
 
-    if let Some((id, title, conv_type)) = existing {
-        return Ok(json(serde_json::json!({
-            "id": id,
-            "title": title.unwrap_or_else(|| "Personal Notes".to_string()),
-            "type": conv_type,
-            "created": false
-        })));
-    }
+    Ok(no_content())
+}
 
-    // Create new personal notes conversation
-    let conv_id: Uuid = sqlx::query_scalar(
-        r#"
-        INSERT INTO chat_conversations (title, type, created_by)
-        VALUES ('Personal Notes', 'personal', $1)
-        RETURNING id
-        "#,
-    )
-    .bind(user.id)
-    .fetch_one(pool)
-    .await
-    .map_err(|e| {
-        rustpress_core::error::Error::internal(format!("Failed to create personal notes: {}", e))
-    })?;
+/// Add tag to conversation
+#[derive(Debug, Deserialize)]
+struct AddTagRequest {
+    tag: String,
+    color: Option<String>,
+}
 
-    // Add user as the only participant
-    sqlx::query("INSERT INTO chat_conversation_participants (conversation_id, user_id, role) VALUES ($1, $2, 'admin')")
-        .bind(conv_id)
-        .bind(user.id)
+async fn add_conversation_tag_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+    Json(payload): Json<AddTagRequest>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let pool = state.db().inner();
+
+    sqlx::query("INSERT INTO chat_conversation_tags (conversation_id, tag, color) VALUES ($1, $2, $3) ON CONFLICT DO NOTHING")
+        .bind(id)
+        .bind(&payload.tag)
+        .bind(&payload.color)
         .execute(pool)
         .await
         .ok();
 
-    Ok(json(serde_json::json!({
-        "id": conv_id,
-        "title": "Personal Notes",
-        "type": "personal",
-        "created": true
-    })))
+    Ok(created(serde_json::json!({ "success": true })))
 }
 
-/// Get online users for creating group chats
-async fn online_users_handler(
+/// Path parameters for tag removal
+#[derive(Debug, Deserialize)]
+struct TagPath {
+    id: Uuid,
+    tag: String,
+}
+
+async fn remove_conversation_tag_handler(
     user: AuthUser,
+    axum::extract::Path(params): axum::extract::Path<TagPath>,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let online_users = state.ws_hub().get_online_users().await;
+    let pool = state.db().inner();
 
-    // Filter out current user and map to response format
-    let users: Vec<serde_json::Value> = online_users
-        .into_iter()
-        .filter(|u| u.user_id != user.id)
-        .map(|u| {
-            serde_json::json!({
-                "id": u.user_id,
-                "username": u.username,
-                "display_name": u.display_name,
-                "avatar_url": u.avatar_url,
-                "status": u.status,
-                "color": u.color,
-                "current_file": u.current_file
-            })
-        })
-        .collect();
+    sqlx::query("DELETE FROM chat_conversation_tags WHERE conversation_id = $1 AND tag = $2")
+        .bind(params.id)
+        .bind(&params.tag)
+        .execute(pool)
+        .await
+        .ok();
 
-    Ok(json(serde_json::json!({
-        "users": users,
-        "count": users.len()
-    })))
+    Ok(no_content())
 }
 
-/// Create group chat request
+/// Edit message request
 #[derive(Debug, Deserialize)]
-struct CreateGroupChatRequest {
-    title: String,
-    participant_ids: Vec<Uuid>,
+struct EditMessageRequest {
+    content: String,
 }
 
-/// Create a new group chat with online users
-async fn create_group_chat_handler(
+async fn edit_message_handler(
     user: AuthUser,
+    PathId(id): PathId,
     State(state): State<AppState>,
-    Json(payload): Json<CreateGroupChatRequest>,
+    Json(payload): Json<EditMessageRequest>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
     let pool = state.db().inner();
 
-    if payload.participant_ids.is_empty() {
-        return Err(rustpress_core::error::Error::validation(
-            "At least one participant is required",
-        )
-        .into());
-    }
+    sqlx::query("UPDATE chat_messages SET content = $1, is_edited = true, edited_at = NOW() WHERE id = $2 AND sender_id = $3")
+        .bind(&payload.content)
+        .bind(id)
+        .bind(user.id)
+        .execute(pool)
+        .await
+        .map_err(|e| rustpress_core::error::Error::internal(format!("Failed to edit: {}", e)))?;
 
-    // Create group conversation
-    let conv_id: Uuid = sqlx::query_scalar(
-        r#"
-        INSERT INTO chat_conversations (title, type, created_by)
-        VALUES ($1, 'group', $2)
-        RETURNING id
-        "#,
-    )
-    .bind(&payload.title)
-    .bind(user.id)
-    .fetch_one(pool)
-    .await
-    .map_err(|e| {
-        rustpress_core::error::Error::internal(format!("Failed to create group chat: {}", e))
-    })?;
+    Ok(json(serde_json::json!({ "success": true })))
+}
 
-    // Add creator as admin
-    sqlx::query("INSERT INTO chat_conversation_participants (conversation_id, user_id, role) VALUES ($1, $2, 'admin')")
-        .bind(conv_id)
+async fn delete_message_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let pool = state.db().inner();
+
+    sqlx::query("UPDATE chat_messages SET deleted_at = NOW() WHERE id = $1 AND sender_id = $2")
+        .bind(id)
         .bind(user.id)
         .execute(pool)
         .await
         .ok();
 
-    // Add other participants
-    for participant_id in &payload.participant_ids {
-        sqlx::query("INSERT INTO chat_conversation_participants (conversation_id, user_id, role) VALUES ($1, $2, 'member')")
-            .bind(conv_id)
-            .bind(participant_id)
-            .execute(pool)
-            .await
-            .ok();
-    }
-
-    // Notify participants via WebSocket
-    let ws_hub = state.ws_hub();
-    for participant_id in &payload.participant_ids {
-        ws_hub
-            .send_to_user(
-                *participant_id,
-                crate::websocket::ServerMessage::ChatMessage {
-                    message: crate::websocket::message::ChatMessageDto {
-                        id: uuid::Uuid::new_v4(),
-                        conversation_id: conv_id,
-                        sender_id: user.id,
-                        sender_name: "System".to_string(),
-                        sender_avatar: None,
-                        content: format!("You have been added to group chat: {}", payload.title),
-                        content_type: "system".to_string(),
-                        reply_to_id: None,
-                        is_pinned: false,
-                        is_edited: false,
-                        reactions: vec![],
-                        created_at: chrono::Utc::now(),
-                    },
-                },
-            )
-            .await;
-    }
+    Ok(no_content())
+}
 
-    Ok(created(serde_json::json!({
-        "id": conv_id,
-        "title": payload.title,
-        "type": "group",
-        "participants": payload.participant_ids.len() + 1
-    })))
+/// Add reaction request
+#[derive(Debug, Deserialize)]
+struct AddReactionRequest {
+    emoji: String,
 }
 
-/// List conversations for the current user
-async fn list_conversations_handler(
+async fn add_reaction_handler(
     user: AuthUser,
+    PathId(id): PathId,
     State(state): State<AppState>,
-    Query(params): Query<ChatPaginationQuery>,
+    Json(payload): Json<AddReactionRequest>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
     let pool = state.db().inner();
 
-    let conversations: Vec<serde_json::Value> =
-        sqlx::query_as::<_, (Uuid, Option<String>, String, chrono::DateTime<chrono::Utc>)>(
-            r#"
-        SELECT c.id, c.title, c.type, c.updated_at
-        FROM chat_conversations c
-        JOIN chat_conversation_participants p ON p.conversation_id = c.id
-        WHERE p.user_id = $1 AND c.is_archived = false
-        ORDER BY c.updated_at DESC
-        LIMIT $2 OFFSET $3
-        "#,
-        )
+    sqlx::query("INSERT INTO chat_message_reactions (message_id, user_id, emoji) VALUES ($1, $2, $3) ON CONFLICT DO NOTHING")
+        .bind(id)
         .bind(user.id)
-        .bind(params.limit)
-        .bind(params.offset)
-        .fetch_all(pool)
+        .bind(&payload.emoji)
+        .execute(pool)
         .await
-        .unwrap_or_default()
-        .into_iter()
-        .map(|(id, title, conv_type, updated_at)| {
-            serde_json::json!({
-                "id": id,
-                "title": title,
-                "type": conv_type,
-                "updated_at": updated_at
-            })
-        })
-        .collect();
+        .ok();
 
-    Ok(json(serde_json::json!({ "conversations": conversations })))
+    Ok(created(serde_json::json!({ "success": true })))
 }
 
-/// Create conversation request
+/// Path params for reaction removal
 #[derive(Debug, Deserialize)]
-struct CreateConversationRequest {
-    title: Option<String>,
-    #[serde(rename = "type")]
-    conversation_type: Option<String>,
-    participant_ids: Vec<Uuid>,
+struct ReactionPath {
+    id: Uuid,
+    emoji: String,
 }
 
-/// Create a new conversation
-async fn create_conversation_handler(
+async fn remove_reaction_handler(
     user: AuthUser,
+    axum::extract::Path(params): axum::extract::Path<ReactionPath>,
     State(state): State<AppState>,
-    Json(payload): Json<CreateConversationRequest>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
     let pool = state.db().inner();
-    let conv_type = payload
-        .conversation_type
-        .unwrap_or_else(|| "direct".to_string());
 
-    // Create conversation
-    let conv_id: Uuid = sqlx::query_scalar(
-        r#"
-        INSERT INTO chat_conversations (title, type, created_by)
-        VALUES ($1, $2, $3)
-        RETURNING id
-        "#,
+    sqlx::query(
+        "DELETE FROM chat_message_reactions WHERE message_id = $1 AND user_id = $2 AND emoji = $3",
     )
-    .bind(&payload.title)
-    .bind(&conv_type)
+    .bind(params.id)
     .bind(user.id)
-    .fetch_one(pool)
+    .bind(&params.emoji)
+    .execute(pool)
     .await
-    .map_err(|e| {
-        rustpress_core::error::Error::internal(format!("Failed to create conversation: {}", e))
-    })?;
+    .ok();
 
-    // Add creator as participant
-    sqlx::query("INSERT INTO chat_conversation_participants (conversation_id, user_id, role) VALUES ($1, $2, 'admin')")
-        .bind(conv_id)
-        .bind(user.id)
-        .execute(pool)
-        .await
-        .ok();
+    Ok(no_content())
+}
 
-    // Add other participants
-    for participant_id in payload.participant_ids {
-        sqlx::query(
-            "INSERT INTO chat_conversation_participants (conversation_id, user_id) VALUES ($1, $2)",
-        )
-        .bind(conv_id)
-        .bind(participant_id)
+async fn star_message_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let pool = state.db().inner();
+
+    sqlx::query("INSERT INTO chat_message_stars (message_id, user_id) VALUES ($1, $2) ON CONFLICT DO NOTHING")
+        .bind(id)
+        .bind(user.id)
         .execute(pool)
         .await
         .ok();
-    }
 
-    Ok(created(serde_json::json!({
-        "id": conv_id,
-        "title": payload.title,
-        "type": conv_type
-    })))
+    Ok(created(serde_json::json!({ "success": true })))
 }
 
-/// Get a specific conversation
-async fn get_conversation_handler(
+async fn unstar_message_handler(
     user: AuthUser,
     PathId(id): PathId,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
     let pool = state.db().inner();
 
-    let conv: Option<(Uuid, Option<String>, String, bool)> = sqlx::query_as(
-        r#"
-        SELECT c.id, c.title, c.type, c.is_archived
-        FROM chat_conversations c
-        JOIN chat_conversation_participants p ON p.conversation_id = c.id
-        WHERE c.id = $1 AND p.user_id = $2
-        "#,
-    )
-    .bind(id)
-    .bind(user.id)
-    .fetch_optional(pool)
-    .await
-    .unwrap_or(None);
-
-    match conv {
-        Some((id, title, conv_type, is_archived)) => Ok(json(serde_json::json!({
-            "id": id,
-            "title": title,
-            "type": conv_type,
-            "is_archived": is_archived
-        }))),
-        None => Err(rustpress_core::error::Error::not_found("Conversation", id.to_string()).into()),
-    }
-}
+    sqlx::query("DELETE FROM chat_message_stars WHERE message_id = $1 AND user_id = $2")
+        .bind(id)
+        .bind(user.id)
+        .execute(pool)
+        .await
+        .ok();
 
-/// Update conversation request
-#[derive(Debug, Deserialize)]
-struct UpdateConversationRequest {
-    title: Option<String>,
+    Ok(no_content())
 }
 
-/// Update a conversation
-async fn update_conversation_handler(
+async fn pin_message_handler(
     user: AuthUser,
     PathId(id): PathId,
     State(state): State<AppState>,
-    Json(payload): Json<UpdateConversationRequest>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
     let pool = state.db().inner();
 
-    sqlx::query("UPDATE chat_conversations SET title = COALESCE($1, title) WHERE id = $2")
-        .bind(&payload.title)
+    sqlx::query("UPDATE chat_messages SET is_pinned = true WHERE id = $1")
         .bind(id)
         .execute(pool)
         .await
-        .map_err(|e| rustpress_core::error::Error::internal(format!("Failed to update: {}", e)))?;
+        .ok();
 
     Ok(json(serde_json::json!({ "success": true })))
 }
 
-/// Archive a conversation
-async fn archive_conversation_handler(
+async fn unpin_message_handler(
     user: AuthUser,
     PathId(id): PathId,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
     let pool = state.db().inner();
 
-    sqlx::query("UPDATE chat_conversations SET is_archived = true WHERE id = $1")
+    sqlx::query("UPDATE chat_messages SET is_pinned = false WHERE id = $1")
         .bind(id)
         .execute(pool)
         .await
-        .map_err(|e| rustpress_core::error::Error::internal(format!("Failed to archive: {}", e)))?;
+        .ok();
 
-    Ok(no_content())
+    Ok(json(serde_json::json!({ "success": true })))
 }
 
-/// Get messages in a conversation
-async fn get_messages_handler(
-    _user: AuthUser,
+/// Set reminder request
+#[derive(Debug, Deserialize)]
+struct SetReminderRequest {
+    remind_at: chrono::DateTime<chrono::Utc>,
+}
+
+async fn set_reminder_handler(
+    user: AuthUser,
     PathId(id): PathId,
     State(state): State<AppState>,
-    Query(params): Query<ChatPaginationQuery>,
+    Json(payload): Json<SetReminderRequest>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
     let pool = state.db().inner();
 
-    let messages: Vec<serde_json::Value> = sqlx::query_as::<
-        _,
-        (
-            Uuid,
-            Uuid,
-            String,
-            String,
-            bool,
-            bool,
-            chrono::DateTime<chrono::Utc>,
-        ),
-    >(
-        r#"
-        SELECT m.id, m.sender_id, m.content, m.content_type, m.is_pinned, m.is_edited, m.created_at
-        FROM chat_messages m
-        WHERE m.conversation_id = $1 AND m.deleted_at IS NULL
-        ORDER BY m.created_at DESC
-        LIMIT $2 OFFSET $3
-        "#,
+    let reminder_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO chat_message_reminders (message_id, user_id, remind_at) VALUES ($1, $2, $3) RETURNING id"
     )
     .bind(id)
-    .bind(params.limit)
-    .bind(params.offset)
-    .fetch_all(pool)
+    .bind(user.id)
+    .bind(payload.remind_at)
+    .fetch_one(pool)
     .await
-    .unwrap_or_default()
-    .into_iter()
-    .map(
-        |(msg_id, sender_id, content, content_type, is_pinned, is_edited, created_at)| {
+    .map_err(|e| rustpress_core::error::Error::internal(format!("Failed to set reminder: {}", e)))?;
+
+    Ok(created(serde_json::json!({ "id": reminder_id })))
+}
+
+/// Chat history query params
+#[derive(Debug, Deserialize)]
+struct ChatHistoryQuery {
+    search: Option<String>,
+    from: Option<chrono::DateTime<chrono::Utc>>,
+    to: Option<chrono::DateTime<chrono::Utc>>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+}
+
+async fn chat_history_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Query(params): Query<ChatHistoryQuery>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let pool = state.db().inner();
+
+    let messages: Vec<serde_json::Value> =
+        sqlx::query_as::<_, (Uuid, Uuid, String, chrono::DateTime<chrono::Utc>)>(
+            r#"
+        SELECT m.id, m.conversation_id, m.content, m.created_at
+        FROM chat_messages m
+        JOIN chat_conversation_participants p ON p.conversation_id = m.conversation_id
+        WHERE p.user_id = $1
+          AND m.deleted_at IS NULL
+          AND ($2::text IS NULL OR m.content ILIKE '%' || $2 || '%')
+          AND ($3::timestamptz IS NULL OR m.created_at >= $3)
+          AND ($4::timestamptz IS NULL OR m.created_at <= $4)
+        ORDER BY m.created_at DESC
+        LIMIT $5 OFFSET $6
+        "#,
+        )
+        .bind(user.id)
+        .bind(&params.search)
+        .bind(params.from)
+        .bind(params.to)
+        .bind(params.limit.unwrap_or(50))
+        .bind(params.offset.unwrap_or(0))
+        .fetch_all(pool)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(id, conv_id, content, created_at)| {
+            serde_json::json!({
+                "id": id,
+                "conversation_id": conv_id,
+                "content": content,
+                "created_at": created_at
+            })
+        })
+        .collect();
+
+    Ok(json(serde_json::json!({ "messages": messages })))
+}
+
+async fn starred_messages_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Query(params): Query<ChatPaginationQuery>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    let pool = state.db().inner();
+
+    let messages: Vec<serde_json::Value> =
+        sqlx::query_as::<_, (Uuid, Uuid, String, chrono::DateTime<chrono::Utc>)>(
+            r#"
+        SELECT m.id, m.conversation_id, m.content, m.created_at
+        FROM chat_messages m
+        JOIN chat_message_stars s ON s.message_id = m.id
+        WHERE s.user_id = $1 AND m.deleted_at IS NULL
+        ORDER BY s.created_at DESC
+        LIMIT $2 OFFSET $3
+        "#,
+        )
+        .bind(user.id)
+        .bind(params.limit)
+        .bind(params.offset)
+        .fetch_all(pool)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(id, conv_id, content, created_at)| {
             serde_json::json!({
-                "id": msg_id,
-                "sender_id": sender_id,
+                "id": id,
+                "conversation_id": conv_id,
                 "content": content,
-                "content_type": content_type,
-                "is_pinned": is_pinned,
-                "is_edited": is_edited,
                 "created_at": created_at
             })
-        },
-    )
-    .collect();
+        })
+        .collect();
 
     Ok(json(serde_json::json!({ "messages": messages })))
 }
 
-/// Send message request
-#[derive(Debug, Deserialize)]
-struct SendMessageRequest {
-    content: String,
-    content_type: Option<String>,
-    reply_to_id: Option<Uuid>,
+// =============================================================================
+// Social Sharing Routes and Handlers
+// =============================================================================
+
+use crate::services::{CardImage, PostSocialInput, ShareQuery, ShareRequest, SocialConfig};
+
+/// Social sharing routes
+fn social_routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/",
+            get(get_social_config_handler).put(update_social_config_handler),
+        )
+        .route("/shares", get(list_social_shares_handler))
+        .route("/shares/:id", delete(cancel_social_share_handler))
+        .route("/shares/:id/retry", post(retry_social_share_handler))
 }
 
-/// Send a message to a conversation
-async fn send_message_handler(
+fn card_response(image: CardImage, cache_control: &'static str) -> Response {
+    (
+        [
+            (header::CONTENT_TYPE, image.content_type),
+            (header::CACHE_CONTROL, cache_control.to_string()),
+        ],
+        image.bytes,
+    )
+        .into_response()
+}
+
+/// Serve the Open Graph image of a published post
+async fn og_image_handler(PathId(id): PathId, State(state): State<AppState>) -> Response {
+    match state.og_images.image(id, false).await {
+        // The URL is versioned, so images can be cached for a while
+        Ok(image) => card_response(image, "public, max-age=86400"),
+        Err(rustpress_core::error::Error::NotFound { .. }) => {
+            axum::http::StatusCode::NOT_FOUND.into_response()
+        }
+        Err(e) => {
+            tracing::warn!(post_id = %id, "OG image failed: {}", e);
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Get the social sharing configuration (access tokens masked)
+async fn get_social_config_handler(
     user: AuthUser,
-    PathId(id): PathId,
     State(state): State<AppState>,
-    Json(payload): Json<SendMessageRequest>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
-
-    let msg_id: Uuid = sqlx::query_scalar(
-        r#"
-        INSERT INTO chat_messages (conversation_id, sender_id, content, content_type, reply_to_id)
-        VALUES ($1, $2, $3, $4, $5)
-        RETURNING id
-        "#,
-    )
-    .bind(id)
-    .bind(user.id)
-    .bind(&payload.content)
-    .bind(payload.content_type.as_deref().unwrap_or("text"))
-    .bind(payload.reply_to_id)
-    .fetch_one(pool)
-    .await
-    .map_err(|e| {
-        rustpress_core::error::Error::internal(format!("Failed to send message: {}", e))
-    })?;
-
-    // Broadcast via WebSocket
-    if let Ok(Some(dto)) = crate::websocket::chat::ChatService::new(pool.clone())
-        .get_message_dto(msg_id, user.id)
-        .await
-    {
-        state
-            .ws_hub
-            .broadcast_to_conversation(
-                id,
-                crate::websocket::ServerMessage::ChatMessage { message: dto },
-                None,
-            )
-            .await;
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can view social sharing settings",
+        ));
     }
 
-    Ok(created(serde_json::json!({ "id": msg_id })))
+    Ok(json(state.social.config().await.masked()))
 }
 
-/// List participants in a conversation
-async fn list_participants_handler(
+/// Update the social sharing configuration
+async fn update_social_config_handler(
     user: AuthUser,
-    PathId(id): PathId,
     State(state): State<AppState>,
+    Json(config): Json<SocialConfig>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
-
-    let participants: Vec<serde_json::Value> = sqlx::query_as::<_, (Uuid, String, Option<String>)>(
-        r#"
-        SELECT u.id, u.username, u.display_name
-        FROM chat_conversation_participants p
-        JOIN users u ON u.id = p.user_id
-        WHERE p.conversation_id = $1
-        "#,
-    )
-    .bind(id)
-    .fetch_all(pool)
-    .await
-    .unwrap_or_default()
-    .into_iter()
-    .map(|(uid, username, display_name)| {
-        serde_json::json!({
-            "id": uid,
-            "username": username,
-            "display_name": display_name
-        })
-    })
-    .collect();
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can change social sharing settings",
+        ));
+    }
 
-    Ok(json(serde_json::json!({ "participants": participants })))
+    Ok(json(state.social.update_config(config).await?))
 }
 
-/// Add participant to conversation
-#[derive(Debug, Deserialize)]
-struct AddParticipantRequest {
-    user_id: Uuid,
+/// A post's sharing settings, how it looks on each network, and its shares
+async fn get_post_social_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_permission(&user, &state, "posts", "edit").await?;
+    check_section_access(&state, &user, Some(id), None).await?;
+    Ok(json(state.social.overview(id).await?))
 }
 
-async fn add_participant_handler(
+/// Replace a post's card text and per-account messages
+async fn update_post_social_handler(
     user: AuthUser,
     PathId(id): PathId,
     State(state): State<AppState>,
-    Json(payload): Json<AddParticipantRequest>,
+    Json(payload): Json<PostSocialInput>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
-
-    sqlx::query("INSERT INTO chat_conversation_participants (conversation_id, user_id) VALUES ($1, $2) ON CONFLICT DO NOTHING")
-        .bind(id)
-        .bind(payload.user_id)
-        .execute(pool)
-        .await
-        .ok();
-
-    Ok(created(serde_json::json!({ "success": true })))
+    require_permission(&user, &state, "posts", "edit").await?;
+    check_section_access(&state, &user, Some(id), None).await?;
+    state
+        .social
+        .save_post_settings(id, user.id, payload)
+        .await?;
+    Ok(json(state.social.overview(id).await?))
 }
 
-/// Path parameters for participant removal
-#[derive(Debug, Deserialize)]
-struct ParticipantPath {
-    id: Uuid,
-    user_id: Uuid,
+/// Preview card of any post, drafts included
+async fn preview_social_card_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<Response> {
+    require_permission(&user, &state, "posts", "edit").await?;
+    check_section_access(&state, &user, Some(id), None).await?;
+    let image = state.og_images.image(id, true).await?;
+    Ok(card_response(image, "private, no-store"))
 }
 
-async fn remove_participant_handler(
+/// Share a post now or at a given time
+async fn share_post_handler(
     user: AuthUser,
-    axum::extract::Path(params): axum::extract::Path<ParticipantPath>,
+    PathId(id): PathId,
     State(state): State<AppState>,
+    Json(payload): Json<ShareRequest>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
-
-    sqlx::query(
-        "DELETE FROM chat_conversation_participants WHERE conversation_id = $1 AND user_id = $2",
-    )
-    .bind(params.id)
-    .bind(params.user_id)
-    .execute(pool)
-    .await
-    .ok();
+    require_permission(&user, &state, "posts", "publish").await?;
+    check_section_access(&state, &user, Some(id), None).await?;
+    let shares = state.social.share(id, user.id, payload).await?;
+    state
+        .publish(
+            user_event(
+                Some(&user),
+                "social.share_queued",
+                serde_json::json!({
+                    "post_id": id,
+                    "share_ids": shares.iter().map(|s| s.id).collect::<Vec<_>>(),
+                }),
+            )
+            .with_aggregate(id, "post"),
+        )
+        .await;
+    Ok(created(shares))
+}
 
-    Ok(no_content())
+/// Shares of all posts, newest first
+async fn list_social_shares_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Query(query): Query<ShareQuery>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_permission(&user, &state, "posts", "edit").await?;
+    let (shares, total) = state.social.list(&query).await?;
+    Ok(paginated(
+        shares,
+        total,
+        query.page.unwrap_or(1).max(1),
+        query.per_page.unwrap_or(20).clamp(1, 100),
+    ))
 }
 
-/// Add tag to conversation
-#[derive(Debug, Deserialize)]
-struct AddTagRequest {
-    tag: String,
-    color: Option<String>,
+/// Cancel a share that has not been sent
+async fn cancel_social_share_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_permission(&user, &state, "posts", "publish").await?;
+    let share = state.social.get(id).await?;
+    check_section_access(&state, &user, Some(share.post_id), None).await?;
+    Ok(json(state.social.cancel(id).await?))
 }
 
-async fn add_conversation_tag_handler(
+/// Send a failed share again
+async fn retry_social_share_handler(
     user: AuthUser,
     PathId(id): PathId,
     State(state): State<AppState>,
-    Json(payload): Json<AddTagRequest>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
+    require_permission(&user, &state, "posts", "publish").await?;
+    let share = state.social.get(id).await?;
+    check_section_access(&state, &user, Some(share.post_id), None).await?;
+    Ok(json(state.social.retry(id).await?))
+}
 
-    sqlx::query("INSERT INTO chat_conversation_tags (conversation_id, tag, color) VALUES ($1, $2, $3) ON CONFLICT DO NOTHING")
-        .bind(id)
-        .bind(&payload.tag)
-        .bind(&payload.color)
-        .execute(pool)
-        .await
-        .ok();
+// =============================================================================
+// Podcast Routes and Handlers
+// =============================================================================
 
-    Ok(created(serde_json::json!({ "success": true })))
+use crate::services::{
+    ByteRange, DownloadQuery, DownloadRequest, EpisodeAudio, EpisodeInput, PodcastConfig,
+};
+
+/// Podcast routes
+fn podcast_routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/",
+            get(get_podcast_config_handler).put(update_podcast_config_handler),
+        )
+        .route("/episodes", get(list_episodes_handler))
+        .route("/stats", get(podcast_stats_handler))
 }
 
-/// Path parameters for tag removal
-#[derive(Debug, Deserialize)]
-struct TagPath {
-    id: Uuid,
-    tag: String,
+/// Podcast feed with the iTunes and Podcast Index namespaces
+async fn podcast_feed_handler(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+) -> Response {
+    let show = state.podcast.config().await;
+    match state.renderer().render_podcast_feed(&show).await {
+        Ok(feed) => conditional_feed_response(feed, &headers),
+        Err(e) => rendered_response(Err(e)),
+    }
 }
 
-async fn remove_conversation_tag_handler(
-    user: AuthUser,
-    axum::extract::Path(params): axum::extract::Path<TagPath>,
+/// Serve the audio of a published episode, with byte ranges, and log the
+/// request for download statistics
+async fn podcast_audio_handler(
     State(state): State<AppState>,
-) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
+    axum::extract::Path(file): axum::extract::Path<String>,
+    request: axum::http::Request<axum::body::Body>,
+) -> Response {
+    use axum::http::{HeaderValue, StatusCode};
+
+    // `{post_id}.{ext}`; the extension is only there for podcast apps
+    let Some(id) = file
+        .split('.')
+        .next()
+        .and_then(|id| Uuid::parse_str(id).ok())
+    else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let episode = match state.podcast.published(id).await {
+        Ok(episode) => episode,
+        Err(rustpress_core::error::Error::NotFound { .. }) => {
+            return StatusCode::NOT_FOUND.into_response();
+        }
+        Err(e) => {
+            tracing::warn!(post_id = %id, "Failed to load episode: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    let audio = match state.podcast.audio(&episode).await {
+        Ok(audio) => audio,
+        Err(rustpress_core::error::Error::NotFound { .. }) => {
+            return StatusCode::NOT_FOUND.into_response();
+        }
+        Err(e) => {
+            tracing::warn!(post_id = %id, "Failed to load episode audio: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
 
-    sqlx::query("DELETE FROM chat_conversation_tags WHERE conversation_id = $1 AND tag = $2")
-        .bind(params.id)
-        .bind(&params.tag)
-        .execute(pool)
-        .await
-        .ok();
+    let (response, bytes_sent) = match audio {
+        // Hosted elsewhere: the listener fetches the whole file from there
+        EpisodeAudio::External(url) => (
+            axum::response::Redirect::temporary(&url).into_response(),
+            episode.file_size,
+        ),
+        EpisodeAudio::Stored {
+            content_type,
+            bytes,
+        } => {
+            let len = bytes.len() as u64;
+            let range = request
+                .headers()
+                .get(header::RANGE)
+                .and_then(|v| v.to_str().ok());
+            let (status, body, content_range) = match ByteRange::parse(range, len) {
+                ByteRange::Full => (StatusCode::OK, bytes, None),
+                ByteRange::Partial { start, end } => (
+                    StatusCode::PARTIAL_CONTENT,
+                    bytes.slice(start as usize..=end as usize),
+                    Some(format!("bytes {}-{}/{}", start, end, len)),
+                ),
+                ByteRange::Unsatisfiable => {
+                    let mut response = StatusCode::RANGE_NOT_SATISFIABLE.into_response();
+                    if let Ok(value) = HeaderValue::from_str(&format!("bytes */{}", len)) {
+                        response.headers_mut().insert(header::CONTENT_RANGE, value);
+                    }
+                    return response;
+                }
+            };
+            let sent = body.len() as i64;
+            let mut response = (
+                status,
+                [
+                    (header::CONTENT_TYPE, content_type),
+                    (header::ACCEPT_RANGES, "bytes".to_string()),
+                    // Shared caches would answer requests the statistics never see
+                    (header::CACHE_CONTROL, "private, max-age=86400".to_string()),
+                ],
+                body,
+            )
+                .into_response();
+            if let Some(Ok(value)) = content_range.map(|v| HeaderValue::from_str(&v)) {
+                response.headers_mut().insert(header::CONTENT_RANGE, value);
+            }
+            (response, sent)
+        }
+    };
 
-    Ok(no_content())
+    // HEAD requests fetch no audio
+    if request.method() == axum::http::Method::GET {
+        let ip = crate::middleware::client_ip(&request, &state.config.server.trusted_proxies);
+        let user_agent = request
+            .headers()
+            .get(header::USER_AGENT)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default();
+        let download = DownloadRequest {
+            ip,
+            user_agent,
+            bytes: bytes_sent,
+        };
+        if let Err(e) = state.podcast.record(&episode, download).await {
+            tracing::warn!(post_id = %id, "Failed to record episode download: {}", e);
+        }
+    }
+    response
 }
 
-/// Edit message request
-#[derive(Debug, Deserialize)]
-struct EditMessageRequest {
-    content: String,
+/// Get the podcast show settings
+async fn get_podcast_config_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can view podcast settings",
+        ));
+    }
+
+    Ok(json(state.podcast.config().await.as_ref().clone()))
 }
 
-async fn edit_message_handler(
+/// Update the podcast show settings
+async fn update_podcast_config_handler(
     user: AuthUser,
-    PathId(id): PathId,
     State(state): State<AppState>,
-    Json(payload): Json<EditMessageRequest>,
+    Json(config): Json<PodcastConfig>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
-
-    sqlx::query("UPDATE chat_messages SET content = $1, is_edited = true, edited_at = NOW() WHERE id = $2 AND sender_id = $3")
-        .bind(&payload.content)
-        .bind(id)
-        .bind(user.id)
-        .execute(pool)
-        .await
-        .map_err(|e| rustpress_core::error::Error::internal(format!("Failed to edit: {}", e)))?;
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can change podcast settings",
+        ));
+    }
 
-    Ok(json(serde_json::json!({ "success": true })))
+    Ok(json(state.podcast.update_config(config).await?))
 }
 
-async fn delete_message_handler(
+/// Episodes with their posts, newest first
+async fn list_episodes_handler(
     user: AuthUser,
-    PathId(id): PathId,
     State(state): State<AppState>,
+    PaginatedQuery(params): PaginatedQuery,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
-
-    sqlx::query("UPDATE chat_messages SET deleted_at = NOW() WHERE id = $1 AND sender_id = $2")
-        .bind(id)
-        .bind(user.id)
-        .execute(pool)
-        .await
-        .ok();
-
-    Ok(no_content())
+    require_permission(&user, &state, "posts", "edit").await?;
+    let page = params.page.max(1);
+    let per_page = params.per_page.clamp(1, 100);
+    let (episodes, total) = state.podcast.list(page, per_page).await?;
+    Ok(paginated(episodes, total, page, per_page))
 }
 
-/// Add reaction request
-#[derive(Debug, Deserialize)]
-struct AddReactionRequest {
-    emoji: String,
+/// Downloads per day, episode and podcast app
+async fn podcast_stats_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Query(query): Query<DownloadQuery>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_permission(&user, &state, "posts", "edit").await?;
+    Ok(json(state.podcast.report(&query).await?))
 }
 
-async fn add_reaction_handler(
+/// The episode enclosure of a post
+async fn get_post_episode_handler(
     user: AuthUser,
     PathId(id): PathId,
     State(state): State<AppState>,
-    Json(payload): Json<AddReactionRequest>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
-
-    sqlx::query("INSERT INTO chat_message_reactions (message_id, user_id, emoji) VALUES ($1, $2, $3) ON CONFLICT DO NOTHING")
-        .bind(id)
-        .bind(user.id)
-        .bind(&payload.emoji)
-        .execute(pool)
-        .await
-        .ok();
-
-    Ok(created(serde_json::json!({ "success": true })))
+    require_permission(&user, &state, "posts", "edit").await?;
+    check_section_access(&state, &user, Some(id), None).await?;
+    Ok(json(state.podcast.episode(id).await?))
 }
 
-/// Path params for reaction removal
-#[derive(Debug, Deserialize)]
-struct ReactionPath {
-    id: Uuid,
-    emoji: String,
+/// Make a post a podcast episode, or replace its enclosure
+async fn update_post_episode_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+    Json(payload): Json<EpisodeInput>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_permission(&user, &state, "posts", "edit").await?;
+    check_section_access(&state, &user, Some(id), None).await?;
+    Ok(json(state.podcast.save(id, user.id, payload).await?))
 }
 
-async fn remove_reaction_handler(
+/// Turn an episode back into a plain post
+async fn delete_post_episode_handler(
     user: AuthUser,
-    axum::extract::Path(params): axum::extract::Path<ReactionPath>,
+    PathId(id): PathId,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
-
-    sqlx::query(
-        "DELETE FROM chat_message_reactions WHERE message_id = $1 AND user_id = $2 AND emoji = $3",
-    )
-    .bind(params.id)
-    .bind(user.id)
-    .bind(&params.emoji)
-    .execute(pool)
-    .await
-    .ok();
-
+    require_permission(&user, &state, "posts", "edit").await?;
+    check_section_access(&state, &user, Some(id), None).await?;
+    state.podcast.remove(id).await?;
     Ok(no_content())
 }
 
-async fn star_message_handler(
+/// Downloads of one episode
+async fn post_episode_stats_handler(
     user: AuthUser,
     PathId(id): PathId,
     State(state): State<AppState>,
+    Query(query): Query<DownloadQuery>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
-
-    sqlx::query("INSERT INTO chat_message_stars (message_id, user_id) VALUES ($1, $2) ON CONFLICT DO NOTHING")
-        .bind(id)
-        .bind(user.id)
-        .execute(pool)
-        .await
-        .ok();
-
-    Ok(created(serde_json::json!({ "success": true })))
+    require_permission(&user, &state, "posts", "edit").await?;
+    check_section_access(&state, &user, Some(id), None).await?;
+    let query = DownloadQuery {
+        post_id: Some(id),
+        ..query
+    };
+    Ok(json(state.podcast.report(&query).await?))
 }
 
-async fn unstar_message_handler(
+/// Who is editing a post together right now
+async fn collaboration_status_handler(
     user: AuthUser,
     PathId(id): PathId,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
-
-    sqlx::query("DELETE FROM chat_message_stars WHERE message_id = $1 AND user_id = $2")
-        .bind(id)
-        .bind(user.id)
-        .execute(pool)
-        .await
-        .ok();
-
-    Ok(no_content())
+    require_permission(&user, &state, "posts", "edit").await?;
+    check_section_access(&state, &user, Some(id), None).await?;
+    Ok(json(state.collab.status(id).await))
 }
 
-async fn pin_message_handler(
+/// Autosaves taken while a post was edited together, newest first
+async fn list_autosaves_handler(
     user: AuthUser,
     PathId(id): PathId,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
-
-    sqlx::query("UPDATE chat_messages SET is_pinned = true WHERE id = $1")
-        .bind(id)
-        .execute(pool)
-        .await
-        .ok();
-
-    Ok(json(serde_json::json!({ "success": true })))
+    require_permission(&user, &state, "posts", "edit").await?;
+    check_section_access(&state, &user, Some(id), None).await?;
+    Ok(json(state.collab.autosaves(id).await?))
 }
 
-async fn unpin_message_handler(
+/// An autosave with its content, for comparing or restoring
+async fn get_autosave_handler(
     user: AuthUser,
     PathId(id): PathId,
     State(state): State<AppState>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
+    require_permission(&user, &state, "posts", "edit").await?;
+    let autosave = state.collab.get_autosave(id).await?;
+    check_section_access(&state, &user, Some(autosave.post_id), None).await?;
+    Ok(json(autosave))
+}
 
-    sqlx::query("UPDATE chat_messages SET is_pinned = false WHERE id = $1")
-        .bind(id)
-        .execute(pool)
-        .await
-        .ok();
+// =============================================================================
+// Event Routes and Handlers
+// =============================================================================
 
-    Ok(json(serde_json::json!({ "success": true })))
-}
+use crate::services::{CalendarQuery, EventInput};
 
-/// Set reminder request
-#[derive(Debug, Deserialize)]
-struct SetReminderRequest {
-    remind_at: chrono::DateTime<chrono::Utc>,
+/// Event routes
+fn event_routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_events_handler))
+        .route("/occurrences", get(event_occurrences_handler))
 }
 
-async fn set_reminder_handler(
-    user: AuthUser,
-    PathId(id): PathId,
+/// Public event handler
+async fn public_event_handler(
     State(state): State<AppState>,
-    Json(payload): Json<SetReminderRequest>,
-) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
+    axum::extract::Path(slug): axum::extract::Path<String>,
+    Query(params): Query<PublicQueryParams>,
+) -> Response {
+    let result = state
+        .renderer()
+        .render_event(&slug, params.preview.as_deref())
+        .await;
+    rendered_response(result)
+}
 
-    let reminder_id: Uuid = sqlx::query_scalar(
-        "INSERT INTO chat_message_reminders (message_id, user_id, remind_at) VALUES ($1, $2, $3) RETURNING id"
-    )
-    .bind(id)
-    .bind(user.id)
-    .bind(payload.remind_at)
-    .fetch_one(pool)
-    .await
-    .map_err(|e| rustpress_core::error::Error::internal(format!("Failed to set reminder: {}", e)))?;
+/// Upcoming events archive
+async fn public_events_handler(
+    State(state): State<AppState>,
+    Query(params): Query<PublicQueryParams>,
+) -> Response {
+    let result = state
+        .renderer()
+        .render_events(false, params.page.unwrap_or(1), params.preview.as_deref())
+        .await;
+    rendered_response(result)
+}
 
-    Ok(created(serde_json::json!({ "id": reminder_id })))
+/// Past events archive
+async fn public_past_events_handler(
+    State(state): State<AppState>,
+    Query(params): Query<PublicQueryParams>,
+) -> Response {
+    let result = state
+        .renderer()
+        .render_events(true, params.page.unwrap_or(1), params.preview.as_deref())
+        .await;
+    rendered_response(result)
 }
 
-/// Chat history query params
-#[derive(Debug, Deserialize)]
-struct ChatHistoryQuery {
-    search: Option<String>,
-    from: Option<chrono::DateTime<chrono::Utc>>,
-    to: Option<chrono::DateTime<chrono::Utc>>,
-    limit: Option<i64>,
-    offset: Option<i64>,
+/// iCalendar feed of the site's events
+async fn public_events_ical_handler(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+) -> Response {
+    match state.renderer().render_events_ical().await {
+        Ok(feed) => conditional_feed_response(feed, &headers),
+        Err(e) => rendered_response(Err(e)),
+    }
 }
 
-async fn chat_history_handler(
+/// Events with their posts, latest first
+async fn list_events_handler(
     user: AuthUser,
     State(state): State<AppState>,
-    Query(params): Query<ChatHistoryQuery>,
+    PaginatedQuery(params): PaginatedQuery,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
-
-    let messages: Vec<serde_json::Value> =
-        sqlx::query_as::<_, (Uuid, Uuid, String, chrono::DateTime<chrono::Utc>)>(
-            r#"
-        SELECT m.id, m.conversation_id, m.content, m.created_at
-        FROM chat_messages m
-        JOIN chat_conversation_participants p ON p.conversation_id = m.conversation_id
-        WHERE p.user_id = $1
-          AND m.deleted_at IS NULL
-          AND ($2::text IS NULL OR m.content ILIKE '%' || $2 || '%')
-          AND ($3::timestamptz IS NULL OR m.created_at >= $3)
-          AND ($4::timestamptz IS NULL OR m.created_at <= $4)
-        ORDER BY m.created_at DESC
-        LIMIT $5 OFFSET $6
-        "#,
-        )
-        .bind(user.id)
-        .bind(&params.search)
-        .bind(params.from)
-        .bind(params.to)
-        .bind(params.limit.unwrap_or(50))
-        .bind(params.offset.unwrap_or(0))
-        .fetch_all(pool)
-        .await
-        .unwrap_or_default()
-        .into_iter()
-        .map(|(id, conv_id, content, created_at)| {
-            serde_json::json!({
-                "id": id,
-                "conversation_id": conv_id,
-                "content": content,
-                "created_at": created_at
-            })
-        })
-        .collect();
+    require_permission(&user, &state, "posts", "edit").await?;
+    let page = params.page.max(1);
+    let per_page = params.per_page.clamp(1, 100);
+    let (events, total) = state.events.list(page, per_page).await?;
+    Ok(paginated(events, total, page, per_page))
+}
 
-    Ok(json(serde_json::json!({ "messages": messages })))
+/// Occurrences of all events in a period, for calendar views
+async fn event_occurrences_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Query(query): Query<CalendarQuery>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_permission(&user, &state, "posts", "edit").await?;
+    Ok(json(state.events.occurrences(&query).await?))
 }
 
-async fn starred_messages_handler(
+/// The schedule, venue and tickets of an event
+async fn get_post_event_handler(
     user: AuthUser,
+    PathId(id): PathId,
     State(state): State<AppState>,
-    Query(params): Query<ChatPaginationQuery>,
 ) -> HttpResult<impl axum::response::IntoResponse> {
-    let pool = state.db().inner();
+    require_permission(&user, &state, "posts", "edit").await?;
+    check_section_access(&state, &user, Some(id), None).await?;
+    Ok(json(state.events.event(id).await?))
+}
 
-    let messages: Vec<serde_json::Value> =
-        sqlx::query_as::<_, (Uuid, Uuid, String, chrono::DateTime<chrono::Utc>)>(
-            r#"
-        SELECT m.id, m.conversation_id, m.content, m.created_at
-        FROM chat_messages m
-        JOIN chat_message_stars s ON s.message_id = m.id
-        WHERE s.user_id = $1 AND m.deleted_at IS NULL
-        ORDER BY s.created_at DESC
-        LIMIT $2 OFFSET $3
-        "#,
-        )
-        .bind(user.id)
-        .bind(params.limit)
-        .bind(params.offset)
-        .fetch_all(pool)
-        .await
-        .unwrap_or_default()
-        .into_iter()
-        .map(|(id, conv_id, content, created_at)| {
-            serde_json::json!({
-                "id": id,
-                "conversation_id": conv_id,
-                "content": content,
-                "created_at": created_at
-            })
-        })
-        .collect();
+/// Make a post an event, or replace its schedule
+async fn update_post_event_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+    Json(payload): Json<EventInput>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_permission(&user, &state, "posts", "edit").await?;
+    check_section_access(&state, &user, Some(id), None).await?;
+    Ok(json(state.events.save(id, user.id, payload).await?))
+}
 
-    Ok(json(serde_json::json!({ "messages": messages })))
+/// Turn an event back into a plain post
+async fn delete_post_event_handler(
+    user: AuthUser,
+    PathId(id): PathId,
+    State(state): State<AppState>,
+) -> HttpResult<impl axum::response::IntoResponse> {
+    require_permission(&user, &state, "posts", "edit").await?;
+    check_section_access(&state, &user, Some(id), None).await?;
+    state.events.remove(id).await?;
+    Ok(no_content())
 }
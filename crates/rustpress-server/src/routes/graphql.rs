@@ -0,0 +1,1033 @@
+//! GraphQL API.
+//!
+//! Exposes posts, users, taxonomies and media through a single
+//! async-graphql schema at `/api/v1/graphql`, with event subscriptions
+//! streamed from the event bus over `/api/v1/graphql/ws` (both the
+//! `graphql-transport-ws` and legacy `graphql-ws` protocols).
+//!
+//! Anonymous callers only see published content; drafts, users and
+//! subscriptions need a bearer token, and email addresses are only
+//! resolved for administrators or the account owner. Profile fields
+//! follow the same per-field visibility as the REST API.
+
+use std::sync::{Arc, OnceLock};
+
+use async_graphql::http::{WebSocket as GraphQlWebSocket, WebSocketProtocols, WsMessage};
+use async_graphql::{
+    ComplexObject, Context, Data, EmptyMutation, Json as GraphQlJson, Object, Schema, SimpleObject,
+    Subscription,
+};
+use axum::{
+    extract::{
+        ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade},
+        RawQuery, State,
+    },
+    http::{header, HeaderMap},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use chrono::{DateTime, Utc};
+use futures::{SinkExt, Stream, StreamExt};
+use rustpress_api::services::media_service::{MediaListParams, MediaResponse, MediaService};
+use rustpress_api::services::post_service::{
+    PostListParams, PostResponse, PostService, TermResponse,
+};
+use rustpress_api::services::user_service::{UserListParams, UserResponse, UserService};
+use rustpress_core::tenant::{current_site, with_site};
+use rustpress_events::DomainEvent;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::error::HttpError;
+use crate::extract::{AuthUser, MaybeAuthUser};
+use crate::services::{ProfileView, ProfileViewer};
+use crate::state::AppState;
+use crate::ws::RECIPIENTS_METADATA;
+
+/// Maximum selection depth accepted for a single operation
+const MAX_QUERY_DEPTH: usize = 12;
+
+/// Maximum complexity score accepted for a single operation
+const MAX_QUERY_COMPLEXITY: usize = 1_000;
+
+/// Largest page size any list field will return
+const MAX_PER_PAGE: u32 = 100;
+
+/// The RustPress GraphQL schema
+pub type RustPressSchema = Schema<QueryRoot, EmptyMutation, SubscriptionRoot>;
+
+/// Build a fresh schema with the standard limits applied.
+///
+/// The schema holds no application state; the handlers attach the
+/// [`AppState`] and the caller's [`Viewer`] to each request.
+pub fn build_schema() -> RustPressSchema {
+    Schema::build(QueryRoot, EmptyMutation, SubscriptionRoot)
+        .limit_depth(MAX_QUERY_DEPTH)
+        .limit_complexity(MAX_QUERY_COMPLEXITY)
+        .finish()
+}
+
+/// Shared schema instance
+fn schema() -> &'static RustPressSchema {
+    static SCHEMA: OnceLock<RustPressSchema> = OnceLock::new();
+    SCHEMA.get_or_init(build_schema)
+}
+
+/// Schema definition language for the current schema
+pub fn sdl() -> String {
+    schema().sdl()
+}
+
+/// The authenticated caller, if any, attached to every GraphQL request
+#[derive(Debug, Clone, Default)]
+pub struct Viewer(pub Option<AuthUser>);
+
+impl Viewer {
+    fn user(&self) -> Option<&AuthUser> {
+        self.0.as_ref()
+    }
+
+    fn require(&self) -> async_graphql::Result<&AuthUser> {
+        self.user()
+            .ok_or_else(|| async_graphql::Error::new("Authentication required"))
+    }
+
+    fn require_admin(&self) -> async_graphql::Result<&AuthUser> {
+        let user = self.require()?;
+        if !user.is_admin() {
+            return Err(async_graphql::Error::new("Administrator access required"));
+        }
+        Ok(user)
+    }
+
+    fn is_admin(&self) -> bool {
+        self.user().is_some_and(|u| u.is_admin())
+    }
+
+    fn can_see_email_of(&self, user_id: Uuid) -> bool {
+        self.user().is_some_and(|u| u.is_admin() || u.id == user_id)
+    }
+
+    fn profile_viewer(&self, profile_user_id: Uuid) -> ProfileViewer {
+        ProfileViewer::for_user(self.user().map(|u| (u.id, u.is_admin())), profile_user_id)
+    }
+
+    fn tenant_id(&self) -> Option<Uuid> {
+        self.user()
+            .and_then(|u| u.claims.tenant_id.as_deref())
+            .and_then(|t| Uuid::parse_str(t).ok())
+    }
+}
+
+fn app_state<'a>(ctx: &Context<'a>) -> &'a AppState {
+    ctx.data_unchecked::<AppState>()
+}
+
+fn viewer<'a>(ctx: &Context<'a>) -> &'a Viewer {
+    ctx.data_unchecked::<Viewer>()
+}
+
+fn clamp_per_page(per_page: Option<u32>) -> Option<u32> {
+    per_page.map(|p| p.clamp(1, MAX_PER_PAGE))
+}
+
+// ============================================================================
+// Object Types
+// ============================================================================
+
+/// A category or tag attached to a post
+#[derive(Debug, Clone, SimpleObject)]
+pub struct Term {
+    pub id: Uuid,
+    pub name: String,
+    pub slug: String,
+}
+
+impl From<TermResponse> for Term {
+    fn from(term: TermResponse) -> Self {
+        Self {
+            id: term.id,
+            name: term.name,
+            slug: term.slug,
+        }
+    }
+}
+
+/// Public author details embedded in a post
+#[derive(Debug, Clone, SimpleObject)]
+pub struct Author {
+    pub id: Uuid,
+    pub name: String,
+    pub avatar_url: Option<String>,
+}
+
+/// A post
+#[derive(Debug, Clone, SimpleObject)]
+pub struct Post {
+    pub id: Uuid,
+    pub author_id: Uuid,
+    pub author: Option<Author>,
+    pub title: String,
+    pub slug: String,
+    pub excerpt: Option<String>,
+    pub content: Option<String>,
+    pub content_format: Option<String>,
+    pub status: String,
+    pub visibility: Option<String>,
+    pub featured_image_id: Option<Uuid>,
+    pub featured_image_url: Option<String>,
+    pub comment_status: Option<String>,
+    pub published_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub version: i64,
+    pub categories: Vec<Term>,
+    pub tags: Vec<Term>,
+}
+
+impl From<PostResponse> for Post {
+    fn from(post: PostResponse) -> Self {
+        Self {
+            id: post.id,
+            author_id: post.author_id,
+            author: post.author.map(|a| Author {
+                id: a.id,
+                name: a.name,
+                avatar_url: a.avatar_url,
+            }),
+            title: post.title,
+            slug: post.slug,
+            excerpt: post.excerpt,
+            content: post.content,
+            content_format: post.content_format,
+            status: post.status,
+            visibility: post.visibility,
+            featured_image_id: post.featured_image_id,
+            featured_image_url: post.featured_image_url,
+            comment_status: post.comment_status,
+            published_at: post.published_at,
+            created_at: post.created_at,
+            updated_at: post.updated_at,
+            version: post.version,
+            categories: post.categories.into_iter().map(Term::from).collect(),
+            tags: post.tags.into_iter().map(Term::from).collect(),
+        }
+    }
+}
+
+/// A page of posts
+#[derive(Debug, Clone, SimpleObject)]
+pub struct PostPage {
+    pub items: Vec<Post>,
+    pub total: u64,
+    pub page: u64,
+    pub per_page: u64,
+    pub total_pages: u64,
+}
+
+/// A user account
+#[derive(Debug, Clone, SimpleObject)]
+#[graphql(complex)]
+pub struct User {
+    pub id: Uuid,
+    pub username: String,
+    pub display_name: Option<String>,
+    pub status: String,
+    pub role: String,
+    pub avatar_url: Option<String>,
+    pub locale: Option<String>,
+    pub timezone: Option<String>,
+    pub created_at: DateTime<Utc>,
+    #[graphql(skip)]
+    pub email: String,
+}
+
+#[ComplexObject]
+impl User {
+    /// Email address; only visible to administrators and the account owner
+    async fn email(&self, ctx: &Context<'_>) -> Option<String> {
+        viewer(ctx)
+            .can_see_email_of(self.id)
+            .then(|| self.email.clone())
+    }
+
+    /// Profile fields visible to the caller
+    async fn profile(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<Profile>> {
+        let viewer = viewer(ctx).profile_viewer(self.id);
+        let profile = app_state(ctx).profiles.view(self.id, viewer).await?;
+        Ok(profile.map(Profile::from))
+    }
+}
+
+/// A profile field value
+#[derive(Debug, Clone, SimpleObject)]
+pub struct ProfileField {
+    pub key: String,
+    pub label: String,
+    /// `text`, `textarea`, `url` or `social_link`
+    pub field_type: String,
+    pub value: String,
+    /// `public`, `members` or `private`
+    pub visibility: String,
+}
+
+/// A link to the user's profile on another site
+#[derive(Debug, Clone, SimpleObject)]
+pub struct SocialLink {
+    pub network: String,
+    pub label: String,
+    pub url: String,
+}
+
+/// A user profile as the caller may see it
+#[derive(Debug, Clone, SimpleObject)]
+pub struct Profile {
+    pub user_id: Uuid,
+    pub username: String,
+    pub display_name: String,
+    pub avatar_url: Option<String>,
+    /// Public author page
+    pub url: String,
+    pub fields: Vec<ProfileField>,
+    pub social_links: Vec<SocialLink>,
+}
+
+impl From<ProfileView> for Profile {
+    fn from(view: ProfileView) -> Self {
+        Self {
+            user_id: view.user_id,
+            username: view.username,
+            display_name: view.display_name,
+            avatar_url: view.avatar_url,
+            url: view.url,
+            fields: view
+                .fields
+                .into_iter()
+                .map(|f| ProfileField {
+                    key: f.key,
+                    label: f.label,
+                    field_type: serde_name(&f.field_type),
+                    value: f.value,
+                    visibility: serde_name(&f.visibility),
+                })
+                .collect(),
+            social_links: view
+                .social_links
+                .into_iter()
+                .map(|l| SocialLink {
+                    network: l.network,
+                    label: l.label,
+                    url: l.url,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Serialized name of a unit enum variant
+fn serde_name<T: serde::Serialize>(value: &T) -> String {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+impl From<UserResponse> for User {
+    fn from(user: UserResponse) -> Self {
+        Self {
+            id: user.id,
+            username: user.username,
+            display_name: user.display_name,
+            status: user.status,
+            role: user.role,
+            avatar_url: user.avatar_url,
+            locale: user.locale,
+            timezone: user.timezone,
+            created_at: user.created_at,
+            email: user.email,
+        }
+    }
+}
+
+/// A page of users
+#[derive(Debug, Clone, SimpleObject)]
+pub struct UserPage {
+    pub items: Vec<User>,
+    pub total: u64,
+    pub page: u64,
+    pub per_page: u64,
+    pub total_pages: u64,
+}
+
+/// A category with its hierarchy and usage count
+#[derive(Debug, Clone, SimpleObject)]
+pub struct Category {
+    pub id: Uuid,
+    pub name: String,
+    pub slug: String,
+    pub description: Option<String>,
+    pub parent_id: Option<Uuid>,
+    pub post_count: i32,
+}
+
+/// A tag with its usage count
+#[derive(Debug, Clone, SimpleObject)]
+pub struct Tag {
+    pub id: Uuid,
+    pub name: String,
+    pub slug: String,
+    pub description: Option<String>,
+    pub post_count: i32,
+}
+
+/// An uploaded media item
+#[derive(Debug, Clone, SimpleObject)]
+pub struct Media {
+    pub id: Uuid,
+    pub uploader_id: Option<Uuid>,
+    pub filename: String,
+    pub original_filename: String,
+    pub mime_type: String,
+    pub media_type: String,
+    pub file_size: i64,
+    pub url: Option<String>,
+    pub alt_text: Option<String>,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub duration: Option<i32>,
+    pub metadata: GraphQlJson<serde_json::Value>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<MediaResponse> for Media {
+    fn from(media: MediaResponse) -> Self {
+        Self {
+            id: media.id,
+            uploader_id: media.uploader_id,
+            filename: media.filename,
+            original_filename: media.original_filename,
+            mime_type: media.mime_type,
+            media_type: media.media_type,
+            file_size: media.file_size,
+            url: media.url,
+            alt_text: media.alt_text,
+            title: media.title,
+            description: media.description,
+            width: media.width,
+            height: media.height,
+            duration: media.duration,
+            metadata: GraphQlJson(media.metadata),
+            created_at: media.created_at,
+            updated_at: media.updated_at,
+        }
+    }
+}
+
+/// A page of media items
+#[derive(Debug, Clone, SimpleObject)]
+pub struct MediaPage {
+    pub items: Vec<Media>,
+    pub total: u64,
+    pub page: u64,
+    pub per_page: u64,
+    pub total_pages: u64,
+}
+
+/// A domain event delivered to subscribers
+#[derive(Debug, Clone, SimpleObject)]
+pub struct Event {
+    pub id: Uuid,
+    pub event_type: String,
+    pub aggregate_id: Option<Uuid>,
+    pub aggregate_type: Option<String>,
+    pub payload: GraphQlJson<serde_json::Value>,
+    pub occurred_at: DateTime<Utc>,
+}
+
+impl From<&DomainEvent> for Event {
+    fn from(event: &DomainEvent) -> Self {
+        Self {
+            id: event.id,
+            event_type: event.event_type.clone(),
+            aggregate_id: event.aggregate_id,
+            aggregate_type: event.aggregate_type.clone(),
+            payload: GraphQlJson(event.payload.clone()),
+            occurred_at: event.occurred_at,
+        }
+    }
+}
+
+// ============================================================================
+// Query
+// ============================================================================
+
+/// `(id, name, slug, description, parent_id, post_count)`
+type CategoryRow = (Uuid, String, String, Option<String>, Option<Uuid>, i32);
+
+/// Root query type
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// List posts. Anonymous callers only see published posts.
+    #[allow(clippy::too_many_arguments)]
+    async fn posts(
+        &self,
+        ctx: &Context<'_>,
+        page: Option<u32>,
+        per_page: Option<u32>,
+        status: Option<String>,
+        author_id: Option<Uuid>,
+        search: Option<String>,
+        sort_by: Option<String>,
+        sort_order: Option<String>,
+    ) -> async_graphql::Result<PostPage> {
+        let status = match viewer(ctx).user() {
+            Some(_) => status,
+            None => Some("published".to_string()),
+        };
+
+        let service = PostService::new(app_state(ctx).db().inner().clone());
+        let result = service
+            .list_posts(PostListParams {
+                page,
+                per_page: clamp_per_page(per_page),
+                status,
+                author_id,
+                search,
+                sort_by,
+                sort_order,
+                filter: None,
+            })
+            .await?;
+
+        Ok(PostPage {
+            items: result.posts.into_iter().map(Post::from).collect(),
+            total: result.total,
+            page: result.page,
+            per_page: result.per_page,
+            total_pages: result.total_pages,
+        })
+    }
+
+    /// Fetch a single post by ID or slug
+    async fn post(
+        &self,
+        ctx: &Context<'_>,
+        id: Option<Uuid>,
+        slug: Option<String>,
+    ) -> async_graphql::Result<Option<Post>> {
+        let service = PostService::new(app_state(ctx).db().inner().clone());
+        let post = match (id, slug) {
+            (Some(id), _) => service.get_post(id).await?,
+            (None, Some(slug)) => service.get_post_by_slug(&slug).await?,
+            (None, None) => return Err("Either `id` or `slug` is required".into()),
+        };
+
+        let authenticated = viewer(ctx).user().is_some();
+        Ok(post
+            .filter(|p| authenticated || p.status == "published")
+            .map(Post::from))
+    }
+
+    /// List users (authenticated)
+    #[allow(clippy::too_many_arguments)]
+    async fn users(
+        &self,
+        ctx: &Context<'_>,
+        page: Option<u32>,
+        per_page: Option<u32>,
+        status: Option<String>,
+        role: Option<String>,
+        search: Option<String>,
+        sort_by: Option<String>,
+        sort_order: Option<String>,
+    ) -> async_graphql::Result<UserPage> {
+        viewer(ctx).require()?;
+
+        let service = UserService::new(app_state(ctx).db().inner().clone());
+        let result = service
+            .list_users(UserListParams {
+                page,
+                per_page: clamp_per_page(per_page),
+                status,
+                role,
+                search,
+                sort_by,
+                sort_order,
+                filter: None,
+            })
+            .await?;
+
+        Ok(UserPage {
+            items: result.users.into_iter().map(User::from).collect(),
+            total: result.total,
+            page: result.page,
+            per_page: result.per_page,
+            total_pages: result.total_pages,
+        })
+    }
+
+    /// Fetch a single user (authenticated)
+    async fn user(&self, ctx: &Context<'_>, id: Uuid) -> async_graphql::Result<Option<User>> {
+        viewer(ctx).require()?;
+
+        let service = UserService::new(app_state(ctx).db().inner().clone());
+        Ok(service.get_user(id).await?.map(User::from))
+    }
+
+    /// The authenticated caller
+    async fn viewer(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<User>> {
+        let Some(user) = viewer(ctx).user() else {
+            return Ok(None);
+        };
+
+        let service = UserService::new(app_state(ctx).db().inner().clone());
+        Ok(service.get_user(user.id).await?.map(User::from))
+    }
+
+    /// Public profile of an author by username
+    async fn author(
+        &self,
+        ctx: &Context<'_>,
+        username: String,
+    ) -> async_graphql::Result<Option<Profile>> {
+        let viewer = match viewer(ctx).user() {
+            Some(_) => ProfileViewer::Member,
+            None => ProfileViewer::Anonymous,
+        };
+        let profile = app_state(ctx)
+            .profiles
+            .view_by_username(&username, viewer)
+            .await?;
+        Ok(profile.map(Profile::from))
+    }
+
+    /// All categories, ordered by name
+    async fn categories(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<Category>> {
+        let rows: Vec<CategoryRow> = sqlx::query_as(
+                r#"
+                SELECT id, name, slug, description, parent_id,
+                       (SELECT COUNT(*) FROM post_categories WHERE category_id = categories.id)::int as post_count
+                FROM categories
+                ORDER BY name
+                "#,
+            )
+            .fetch_all(app_state(ctx).db().inner())
+            .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(
+                |(id, name, slug, description, parent_id, post_count)| Category {
+                    id,
+                    name,
+                    slug,
+                    description,
+                    parent_id,
+                    post_count,
+                },
+            )
+            .collect())
+    }
+
+    /// All tags, ordered by name
+    async fn tags(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<Tag>> {
+        let rows: Vec<(Uuid, String, String, Option<String>, i32)> = sqlx::query_as(
+            r#"
+            SELECT id, name, slug, description,
+                   (SELECT COUNT(*) FROM post_tags WHERE tag_id = tags.id)::int as post_count
+            FROM tags
+            ORDER BY name
+            "#,
+        )
+        .fetch_all(app_state(ctx).db().inner())
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(id, name, slug, description, post_count)| Tag {
+                id,
+                name,
+                slug,
+                description,
+                post_count,
+            })
+            .collect())
+    }
+
+    /// List media items
+    #[allow(clippy::too_many_arguments)]
+    async fn media(
+        &self,
+        ctx: &Context<'_>,
+        page: Option<u32>,
+        per_page: Option<u32>,
+        media_type: Option<String>,
+        mime_type: Option<String>,
+        uploader_id: Option<Uuid>,
+        search: Option<String>,
+        sort_by: Option<String>,
+        sort_order: Option<String>,
+    ) -> async_graphql::Result<MediaPage> {
+        let service = MediaService::new(app_state(ctx).db().inner().clone());
+        let result = service
+            .list_media(MediaListParams {
+                page,
+                per_page: clamp_per_page(per_page),
+                media_type,
+                mime_type,
+                uploader_id,
+                search,
+                sort_by,
+                sort_order,
+                ..Default::default()
+            })
+            .await?;
+
+        Ok(MediaPage {
+            items: result.items.into_iter().map(Media::from).collect(),
+            total: result.total,
+            page: result.page,
+            per_page: result.per_page,
+            total_pages: result.total_pages,
+        })
+    }
+
+    /// Fetch a single media item
+    async fn media_item(
+        &self,
+        ctx: &Context<'_>,
+        id: Uuid,
+    ) -> async_graphql::Result<Option<Media>> {
+        let service = MediaService::new(app_state(ctx).db().inner().clone());
+        Ok(service.get_media(id).await?.map(Media::from))
+    }
+}
+
+// ============================================================================
+// Subscription
+// ============================================================================
+
+/// Root subscription type
+pub struct SubscriptionRoot;
+
+#[Subscription]
+impl SubscriptionRoot {
+    /// Stream domain events as they are published (administrators only).
+    ///
+    /// `types` filters by event type; a trailing `*` matches a prefix,
+    /// e.g. `post.*`. Events scoped to another tenant are never delivered.
+    async fn events(
+        &self,
+        ctx: &Context<'_>,
+        types: Option<Vec<String>>,
+    ) -> async_graphql::Result<impl Stream<Item = Event>> {
+        let viewer = viewer(ctx);
+        viewer.require_admin()?;
+
+        let filter = EventFilter {
+            types: types.unwrap_or_default(),
+            tenant_id: viewer.tenant_id(),
+            admin: viewer.is_admin(),
+            user_id: viewer.user().map(|u| u.id),
+        };
+        Ok(event_stream(
+            app_state(ctx).event_bus.subscribe_broadcast(),
+            filter,
+        ))
+    }
+
+    /// Stream posts as they are published
+    async fn post_published(&self, ctx: &Context<'_>) -> impl Stream<Item = Event> {
+        let filter = EventFilter {
+            types: vec!["post.published".to_string()],
+            tenant_id: viewer(ctx).tenant_id(),
+            admin: false,
+            user_id: viewer(ctx).user().map(|u| u.id),
+        };
+        event_stream(app_state(ctx).event_bus.subscribe_broadcast(), filter)
+    }
+}
+
+/// Which events a subscriber receives
+#[derive(Debug, Clone)]
+struct EventFilter {
+    types: Vec<String>,
+    tenant_id: Option<Uuid>,
+    admin: bool,
+    user_id: Option<Uuid>,
+}
+
+impl EventFilter {
+    fn matches(&self, event: &DomainEvent) -> bool {
+        if let Some(event_tenant) = event.tenant_id {
+            if !self.admin && self.tenant_id != Some(event_tenant) {
+                return false;
+            }
+        }
+
+        // Events addressed to particular users only reach those users
+        if let Some(recipients) = event.metadata.data.get(RECIPIENTS_METADATA) {
+            let recipients: Vec<Uuid> =
+                serde_json::from_value(recipients.clone()).unwrap_or_default();
+            if !self.user_id.is_some_and(|id| recipients.contains(&id)) {
+                return false;
+            }
+        }
+
+        self.types.is_empty()
+            || self
+                .types
+                .iter()
+                .any(|pattern| match pattern.strip_suffix('*') {
+                    Some(prefix) => event.event_type.starts_with(prefix),
+                    None => event.event_type == *pattern,
+                })
+    }
+}
+
+fn event_stream(
+    receiver: broadcast::Receiver<Arc<DomainEvent>>,
+    filter: EventFilter,
+) -> impl Stream<Item = Event> {
+    futures::stream::unfold(receiver, move |mut receiver| {
+        let filter = filter.clone();
+        async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(event) if filter.matches(&event) => {
+                        return Some((Event::from(event.as_ref()), receiver));
+                    }
+                    Ok(_) => continue,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::warn!(skipped, "GraphQL subscriber lagged behind event bus");
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        }
+    })
+}
+
+// ============================================================================
+// HTTP and WebSocket Handlers
+// ============================================================================
+
+/// GraphQL routes
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(graphql_get_handler).post(graphql_post_handler))
+        .route("/schema.graphql", get(graphql_sdl_handler))
+        .route("/ws", get(graphql_ws_handler))
+}
+
+async fn graphql_post_handler(
+    State(state): State<AppState>,
+    MaybeAuthUser(user): MaybeAuthUser,
+    Json(request): Json<async_graphql::Request>,
+) -> Json<async_graphql::Response> {
+    let request = request.data(state).data(Viewer(user));
+    Json(schema().execute(request).await)
+}
+
+async fn graphql_get_handler(
+    State(state): State<AppState>,
+    MaybeAuthUser(user): MaybeAuthUser,
+    RawQuery(query): RawQuery,
+) -> Result<Json<async_graphql::Response>, HttpError> {
+    let request = async_graphql::http::parse_query_string(query.as_deref().unwrap_or_default())
+        .map_err(|e| HttpError::bad_request(e.to_string()))?;
+    let request = request.data(state).data(Viewer(user));
+    Ok(Json(schema().execute(request).await))
+}
+
+async fn graphql_sdl_handler() -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, "text/plain; charset=utf-8")], sdl())
+}
+
+async fn graphql_ws_handler(
+    ws: WebSocketUpgrade,
+    headers: HeaderMap,
+    State(state): State<AppState>,
+) -> Response {
+    let Some(protocol) = negotiate_protocol(&headers) else {
+        return HttpError::bad_request("Unsupported GraphQL WebSocket protocol").into_response();
+    };
+
+    // The socket outlives the request, so it keeps the request's site
+    let site = current_site();
+    ws.protocols(async_graphql::http::ALL_WEBSOCKET_PROTOCOLS)
+        .on_upgrade(move |socket| with_site(site, handle_graphql_socket(socket, state, protocol)))
+}
+
+/// Pick the first GraphQL sub-protocol offered by the client
+fn negotiate_protocol(headers: &HeaderMap) -> Option<WebSocketProtocols> {
+    headers
+        .get(header::SEC_WEBSOCKET_PROTOCOL)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| {
+            value
+                .split(',')
+                .find_map(|p| p.trim().parse::<WebSocketProtocols>().ok())
+        })
+}
+
+async fn handle_graphql_socket(socket: WebSocket, state: AppState, protocol: WebSocketProtocols) {
+    let (mut sink, stream) = socket.split();
+
+    let input = stream
+        .take_while(|message| futures::future::ready(message.is_ok()))
+        .filter_map(|message| async move {
+            match message {
+                Ok(Message::Text(text)) => Some(text.into_bytes()),
+                Ok(Message::Binary(bytes)) => Some(bytes),
+                _ => None,
+            }
+        });
+
+    let init_state = state.clone();
+    let mut output = Box::pin(
+        GraphQlWebSocket::new(schema().clone(), input, protocol)
+            .connection_data({
+                let mut data = Data::default();
+                data.insert(state);
+                data
+            })
+            .on_connection_init(move |payload| async move {
+                let mut data = Data::default();
+                data.insert(authenticate_connection(&init_state, &payload).await?);
+                Ok(data)
+            }),
+    );
+
+    while let Some(message) = output.next().await {
+        let message = match message {
+            WsMessage::Text(text) => Message::Text(text),
+            WsMessage::Close(code, reason) => Message::Close(Some(CloseFrame {
+                code,
+                reason: reason.into(),
+            })),
+        };
+        if sink.send(message).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// Resolve the viewer from a `connection_init` payload.
+///
+/// Accepts either `{"Authorization": "Bearer <token>"}` or
+/// `{"token": "<token>"}`; a payload without credentials yields an
+/// anonymous viewer, while an invalid token rejects the connection.
+async fn authenticate_connection(
+    state: &AppState,
+    payload: &serde_json::Value,
+) -> async_graphql::Result<Viewer> {
+    let token = payload
+        .get("Authorization")
+        .or_else(|| payload.get("authorization"))
+        .and_then(|v| v.as_str())
+        .map(|v| v.strip_prefix("Bearer ").unwrap_or(v))
+        .or_else(|| payload.get("token").and_then(|v| v.as_str()));
+
+    let Some(token) = token else {
+        return Ok(Viewer::default());
+    };
+
+    let claims = state
+        .jwt
+        .validate_access_token(token)
+        .map_err(|_| async_graphql::Error::new("Invalid or expired token"))?;
+    let id = Uuid::parse_str(&claims.sub)
+        .map_err(|_| async_graphql::Error::new("Invalid user ID in token"))?;
+    let email = claims
+        .custom
+        .get("email")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+    let roles = claims.role.iter().cloned().collect();
+
+    let user = AuthUser {
+        id,
+        email,
+        roles,
+        claims,
+        groups: Default::default(),
+    };
+    let user = user.with_group_grants(state).await;
+    user.check_site_member(state)
+        .await
+        .map_err(|e| async_graphql::Error::new(e.body.message))?;
+    Ok(Viewer(Some(user)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schema_exposes_core_types() {
+        let sdl = sdl();
+        for needle in [
+            "type Post",
+            "type User",
+            "type Category",
+            "type Tag",
+            "type Media",
+            "type Profile",
+            "type SubscriptionRoot",
+            "events(types: [String!]): Event!",
+        ] {
+            assert!(sdl.contains(needle), "missing `{needle}` in SDL");
+        }
+    }
+
+    #[test]
+    fn test_event_filter() {
+        let tenant = Uuid::new_v4();
+        let event = DomainEvent::new("post.published", serde_json::json!({}));
+        let scoped = DomainEvent::new("post.updated", serde_json::json!({})).with_tenant(tenant);
+
+        let all = EventFilter {
+            types: Vec::new(),
+            tenant_id: None,
+            admin: false,
+            user_id: None,
+        };
+        assert!(all.matches(&event));
+        assert!(!all.matches(&scoped));
+
+        let posts = EventFilter {
+            types: vec!["post.*".to_string()],
+            tenant_id: Some(tenant),
+            admin: false,
+            user_id: None,
+        };
+        assert!(posts.matches(&event));
+        assert!(posts.matches(&scoped));
+
+        let exact = EventFilter {
+            types: vec!["user.created".to_string()],
+            tenant_id: None,
+            admin: true,
+            user_id: None,
+        };
+        assert!(!exact.matches(&event));
+
+        let member = Uuid::new_v4();
+        let notification = DomainEvent::new("group.notification", serde_json::json!({}))
+            .with_metadata(RECIPIENTS_METADATA, serde_json::json!([member]));
+        assert!(!all.matches(&notification));
+        let own = EventFilter {
+            user_id: Some(member),
+            ..all
+        };
+        assert!(own.matches(&notification));
+    }
+}
@@ -5,6 +5,11 @@
 //! - Request timing patterns
 //! - Header anomaly detection
 //! - Honeypot field detection
+//! - Optional IP lists for verified crawlers and known bad actors
+//!
+//! Every request is tagged with a [`TrafficClass`] so downstream middleware
+//! and handlers can keep bots out of first-party analytics and rate-limit
+//! them in their own buckets.
 
 use axum::{
     body::Body,
@@ -17,10 +22,16 @@ use chrono::{DateTime, Utc};
 use once_cell::sync::Lazy;
 use parking_lot::RwLock;
 use regex::Regex;
+use rustpress_auth::IpPattern;
+use rustpress_events::{DomainEvent, EventBus};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::net::IpAddr;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+
+use crate::services::GeoIpService;
 
 /// Bot detection configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -43,6 +54,12 @@ pub struct BotDetectionConfig {
     pub min_request_interval_ms: u64,
     /// Maximum requests per minute from same IP
     pub max_requests_per_minute: u32,
+    /// IPs or CIDR ranges of verified crawlers
+    #[serde(default)]
+    pub known_bot_ips: Vec<String>,
+    /// IPs or CIDR ranges always treated as suspected bots
+    #[serde(default)]
+    pub suspected_bot_ips: Vec<String>,
 }
 
 impl Default for BotDetectionConfig {
@@ -74,10 +91,65 @@ impl Default for BotDetectionConfig {
             ],
             min_request_interval_ms: 50,
             max_requests_per_minute: 120,
+            known_bot_ips: Vec::new(),
+            suspected_bot_ips: Vec::new(),
         }
     }
 }
 
+/// Maximum number of distinct bot user agents kept in traffic stats
+const MAX_TRACKED_AGENTS: usize = 200;
+
+/// Number of user agents reported in a stats snapshot
+const TOP_AGENTS: usize = 20;
+
+/// Event type of the periodic page view summary for live dashboards
+pub const ANALYTICS_PULSE_EVENT: &str = "analytics.pulse";
+
+/// How often an analytics pulse is considered
+pub const ANALYTICS_PULSE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Traffic classification attached to every request
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrafficClass {
+    /// Regular visitor
+    #[default]
+    Human,
+    /// Self-identified or verified crawler
+    KnownBot,
+    /// Automated traffic detected by heuristics or IP lists
+    SuspectedBot,
+}
+
+impl TrafficClass {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TrafficClass::Human => "human",
+            TrafficClass::KnownBot => "known_bot",
+            TrafficClass::SuspectedBot => "suspected_bot",
+        }
+    }
+
+    pub fn is_bot(&self) -> bool {
+        *self != TrafficClass::Human
+    }
+
+    /// Only human traffic counts toward first-party analytics
+    pub fn counts_in_analytics(&self) -> bool {
+        *self == TrafficClass::Human
+    }
+
+    /// Class of a request tagged by the middleware (human if detection did not run)
+    pub fn of<B>(request: &Request<B>) -> Self {
+        request
+            .extensions()
+            .get::<TrafficClass>()
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
 /// Bot score result
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BotScore {
@@ -89,6 +161,9 @@ pub struct BotScore {
     pub is_allowed_bot: bool,
     /// Reasons for the score
     pub signals: Vec<BotSignal>,
+    /// Resulting traffic classification
+    #[serde(default)]
+    pub class: TrafficClass,
 }
 
 impl BotScore {
@@ -98,6 +173,7 @@ impl BotScore {
             is_bot: false,
             is_allowed_bot: false,
             signals: Vec::new(),
+            class: TrafficClass::Human,
         }
     }
 
@@ -146,6 +222,10 @@ pub enum BotSignal {
     HoneypotTriggered(String),
     /// Connection pattern anomaly
     ConnectionAnomaly(String),
+    /// Client IP is on the verified crawler list
+    KnownBotIp(String),
+    /// Client IP is on the suspected bot list
+    SuspectedBotIp(String),
 }
 
 impl BotSignal {
@@ -164,6 +244,8 @@ impl BotSignal {
             BotSignal::HeaderOrderAnomaly => 20,
             BotSignal::HoneypotTriggered(_) => 80,
             BotSignal::ConnectionAnomaly(_) => 25,
+            BotSignal::KnownBotIp(_) => 0,
+            BotSignal::SuspectedBotIp(_) => 100,
         }
     }
 
@@ -182,6 +264,8 @@ impl BotSignal {
             BotSignal::HeaderOrderAnomaly => "header_order_anomaly",
             BotSignal::HoneypotTriggered(_) => "honeypot_triggered",
             BotSignal::ConnectionAnomaly(_) => "connection_anomaly",
+            BotSignal::KnownBotIp(_) => "known_bot_ip",
+            BotSignal::SuspectedBotIp(_) => "suspected_bot_ip",
         }
     }
 }
@@ -236,13 +320,129 @@ impl RequestTracker {
     }
 }
 
+/// Requests seen per user agent
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentCount {
+    pub user_agent: String,
+    pub class: TrafficClass,
+    pub requests: u64,
+}
+
+/// Snapshot of bot traffic since startup or the last reset
+#[derive(Debug, Clone, Serialize)]
+pub struct BotTrafficStats {
+    pub since: DateTime<Utc>,
+    /// Requests per traffic class
+    pub requests: HashMap<TrafficClass, u64>,
+    /// Page views counted for first-party analytics (humans only)
+    pub page_views: u64,
+    /// Page views excluded from analytics because they came from bots
+    pub excluded_page_views: u64,
+    /// Requests rejected because bot blocking is enabled
+    pub blocked: u64,
+    /// How often each detection signal fired
+    pub signals: HashMap<String, u64>,
+    /// Busiest bot user agents
+    pub top_agents: Vec<AgentCount>,
+    /// Counted page views per country, when GeoIP is enabled
+    pub countries: HashMap<String, u64>,
+}
+
+/// Running counters behind [`BotTrafficStats`]
+struct TrafficRecorder {
+    since: DateTime<Utc>,
+    requests: HashMap<TrafficClass, u64>,
+    page_views: u64,
+    excluded_page_views: u64,
+    blocked: u64,
+    signals: HashMap<String, u64>,
+    agents: HashMap<String, (TrafficClass, u64)>,
+    countries: HashMap<String, u64>,
+}
+
+impl TrafficRecorder {
+    fn new() -> Self {
+        Self {
+            since: Utc::now(),
+            requests: HashMap::new(),
+            page_views: 0,
+            excluded_page_views: 0,
+            blocked: 0,
+            signals: HashMap::new(),
+            agents: HashMap::new(),
+            countries: HashMap::new(),
+        }
+    }
+
+    fn record(&mut self, score: &BotScore, user_agent: Option<&str>) {
+        *self.requests.entry(score.class).or_default() += 1;
+        for signal in &score.signals {
+            *self.signals.entry(signal.name().to_string()).or_default() += 1;
+        }
+
+        if score.class.is_bot() {
+            let agent = user_agent.unwrap_or("(none)");
+            if let Some(entry) = self.agents.get_mut(agent) {
+                entry.1 += 1;
+            } else if self.agents.len() < MAX_TRACKED_AGENTS {
+                self.agents.insert(agent.to_string(), (score.class, 1));
+            }
+        }
+    }
+
+    fn record_page_view(&mut self, class: TrafficClass, country: Option<String>) {
+        if class.counts_in_analytics() {
+            self.page_views += 1;
+            if let Some(country) = country {
+                *self.countries.entry(country).or_default() += 1;
+            }
+        } else {
+            self.excluded_page_views += 1;
+        }
+    }
+
+    fn snapshot(&self) -> BotTrafficStats {
+        let mut top_agents: Vec<AgentCount> = self
+            .agents
+            .iter()
+            .map(|(agent, (class, requests))| AgentCount {
+                user_agent: agent.clone(),
+                class: *class,
+                requests: *requests,
+            })
+            .collect();
+        top_agents.sort_by(|a, b| {
+            b.requests
+                .cmp(&a.requests)
+                .then_with(|| a.user_agent.cmp(&b.user_agent))
+        });
+        top_agents.truncate(TOP_AGENTS);
+
+        BotTrafficStats {
+            since: self.since,
+            requests: self.requests.clone(),
+            page_views: self.page_views,
+            excluded_page_views: self.excluded_page_views,
+            blocked: self.blocked,
+            signals: self.signals.clone(),
+            top_agents,
+            countries: self.countries.clone(),
+        }
+    }
+}
+
 /// Bot detection middleware
 #[derive(Clone)]
 pub struct BotDetectionMiddleware {
     config: Arc<BotDetectionConfig>,
     tracker: Arc<RwLock<RequestTracker>>,
+    stats: Arc<RwLock<TrafficRecorder>>,
     allowed_bot_patterns: Vec<Regex>,
     suspicious_patterns: Vec<Regex>,
+    known_bot_ips: Vec<IpPattern>,
+    suspected_bot_ips: Vec<IpPattern>,
+    geoip: Option<Arc<GeoIpService>>,
+    trusted_proxies: Arc<Vec<IpAddr>>,
 }
 
 impl BotDetectionMiddleware {
@@ -259,14 +459,38 @@ impl BotDetectionMiddleware {
             .filter_map(|p| Regex::new(&format!("(?i){}", regex::escape(p))).ok())
             .collect();
 
+        let known_bot_ips = parse_ip_list(&config.known_bot_ips);
+        let suspected_bot_ips = parse_ip_list(&config.suspected_bot_ips);
+
         Self {
             config: Arc::new(config),
             tracker: Arc::new(RwLock::new(RequestTracker::new())),
+            stats: Arc::new(RwLock::new(TrafficRecorder::new())),
             allowed_bot_patterns,
             suspicious_patterns,
+            known_bot_ips,
+            suspected_bot_ips,
+            geoip: None,
+            trusted_proxies: Arc::new(Vec::new()),
         }
     }
 
+    /// Break counted page views down by country
+    pub fn with_geoip(mut self, geoip: Arc<GeoIpService>) -> Self {
+        self.geoip = Some(geoip);
+        self
+    }
+
+    /// Read the client address from `X-Forwarded-For` only behind these proxies
+    pub fn with_trusted_proxies(mut self, trusted_proxies: Vec<IpAddr>) -> Self {
+        self.trusted_proxies = Arc::new(trusted_proxies);
+        self
+    }
+
+    fn client_ip(&self, request: &Request<Body>) -> Option<IpAddr> {
+        crate::middleware::client_ip(request, &self.trusted_proxies)
+    }
+
     /// Analyze a request for bot indicators
     pub fn analyze(&self, request: &Request<Body>, client_ip: &str) -> BotScore {
         let mut score = BotScore::new();
@@ -286,6 +510,9 @@ impl BotDetectionMiddleware {
             self.analyze_timing(client_ip, &mut score);
         }
 
+        // IP lists
+        self.analyze_ip(client_ip, &mut score);
+
         // Finalize score
         if score.is_allowed_bot {
             score.score = 0; // Reset score for allowed bots
@@ -294,9 +521,99 @@ impl BotDetectionMiddleware {
             score.finalize(self.config.bot_threshold);
         }
 
+        score.class = if score.is_allowed_bot
+            || score
+                .signals
+                .iter()
+                .any(|s| matches!(s, BotSignal::KnownBot(_)))
+        {
+            TrafficClass::KnownBot
+        } else if score.is_bot {
+            TrafficClass::SuspectedBot
+        } else {
+            TrafficClass::Human
+        };
+        // A self-identified bot is a bot even below the score threshold
+        score.is_bot = score.class.is_bot();
+
         score
     }
 
+    /// Record a classified request in the traffic stats
+    pub fn record(&self, score: &BotScore, request: &Request<Body>) {
+        let user_agent = request
+            .headers()
+            .get(header::USER_AGENT)
+            .and_then(|v| v.to_str().ok());
+        self.stats.write().record(score, user_agent);
+    }
+
+    /// Record a served page view; bot views are counted as excluded
+    pub fn record_page_view(&self, class: TrafficClass, client_ip: &str) {
+        // Only counted page views are located, and only as far as the
+        // privacy settings allow
+        let country = match (&self.geoip, client_ip.parse::<IpAddr>()) {
+            (Some(geoip), Ok(ip)) if class.counts_in_analytics() => geoip.country_for_storage(ip),
+            _ => None,
+        };
+        self.stats.write().record_page_view(class, country);
+    }
+
+    /// Snapshot of bot traffic stats
+    pub fn stats(&self) -> BotTrafficStats {
+        self.stats.read().snapshot()
+    }
+
+    /// Clear bot traffic stats
+    pub fn reset_stats(&self) {
+        *self.stats.write() = TrafficRecorder::new();
+    }
+
+    /// Page views counted since the previous pulse; `None` when there
+    /// were none. `last` holds the counters seen by the previous pulse.
+    fn analytics_pulse(&self, last: &mut (u64, u64)) -> Option<DomainEvent> {
+        let stats = self.stats.read();
+        let current = (stats.page_views, stats.excluded_page_views);
+        // Counters start over after a stats reset
+        let previous = if current.0 < last.0 || current.1 < last.1 {
+            (0, 0)
+        } else {
+            *last
+        };
+        *last = current;
+        if current == previous {
+            return None;
+        }
+
+        Some(DomainEvent::new(
+            ANALYTICS_PULSE_EVENT,
+            serde_json::json!({
+                "page_views": current.0 - previous.0,
+                "excluded_page_views": current.1 - previous.1,
+                "total_page_views": current.0,
+                "since": stats.since,
+            }),
+        ))
+    }
+
+    /// Publish an [`ANALYTICS_PULSE_EVENT`] on every tick that counted page views
+    pub fn spawn_analytics_pulse(&self, bus: Arc<EventBus>, every: Duration) -> JoinHandle<()> {
+        let detector = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(every);
+            let mut last = (0, 0);
+            loop {
+                ticker.tick().await;
+                let Some(event) = detector.analytics_pulse(&mut last) else {
+                    continue;
+                };
+                if let Err(e) = bus.publish(event).await {
+                    tracing::warn!("Failed to publish analytics pulse: {}", e);
+                }
+            }
+        })
+    }
+
     fn analyze_user_agent(&self, request: &Request<Body>, score: &mut BotScore) {
         let user_agent = request
             .headers()
@@ -369,6 +686,19 @@ impl BotDetectionMiddleware {
         }
     }
 
+    fn analyze_ip(&self, client_ip: &str, score: &mut BotScore) {
+        let Ok(ip) = client_ip.parse::<IpAddr>() else {
+            return;
+        };
+
+        if self.known_bot_ips.iter().any(|p| p.matches(&ip)) {
+            score.add_signal(BotSignal::KnownBotIp(client_ip.to_string()));
+            score.is_allowed_bot = true;
+        } else if self.suspected_bot_ips.iter().any(|p| p.matches(&ip)) {
+            score.add_signal(BotSignal::SuspectedBotIp(client_ip.to_string()));
+        }
+    }
+
     /// Periodic cleanup of old tracking data
     pub fn cleanup(&self) {
         self.tracker.write().cleanup();
@@ -394,44 +724,110 @@ fn is_known_bot_ua(ua: &str) -> bool {
     BOT_PATTERNS.iter().any(|p| p.is_match(ua))
 }
 
+/// Parse IP list entries (single addresses or CIDR ranges), skipping invalid ones
+fn parse_ip_list(entries: &[String]) -> Vec<IpPattern> {
+    entries
+        .iter()
+        .filter_map(|entry| {
+            let entry = entry.trim();
+            let pattern = if entry.contains('/') {
+                IpPattern::from_cidr_string(entry)
+            } else {
+                entry.parse().ok().map(IpPattern::single)
+            };
+            if pattern.is_none() {
+                tracing::warn!(entry = %entry, "Ignoring invalid bot IP list entry");
+            }
+            pattern
+        })
+        .collect()
+}
+
+/// Whether a request is a public page view for analytics purposes
+pub fn is_page_view(method: &str, path: &str) -> bool {
+    const NON_PAGE_PREFIXES: &[&str] = &[
+        "/api", "/admin", "/health", "/metrics", "/ws", "/static", "/assets", "/uploads",
+    ];
+
+    if method != "GET" {
+        return false;
+    }
+    if NON_PAGE_PREFIXES
+        .iter()
+        .any(|prefix| path == *prefix || path.starts_with(&format!("{}/", prefix)))
+    {
+        return false;
+    }
+    // Files such as robots.txt, sitemap.xml or images are not page views
+    let last_segment = path.rsplit('/').next().unwrap_or("");
+    !last_segment.contains('.')
+}
+
 /// Bot detection middleware function
 pub async fn bot_detection(
     State(detector): State<BotDetectionMiddleware>,
     request: Request<Body>,
     next: Next,
 ) -> Response {
-    let client_ip = request
-        .headers()
-        .get("x-forwarded-for")
-        .and_then(|v| v.to_str().ok())
-        .and_then(|s| s.split(',').next())
-        .map(|s| s.trim().to_string())
+    let client_ip = detector
+        .client_ip(&request)
+        .map(|ip| ip.to_string())
         .unwrap_or_else(|| "unknown".to_string());
 
     let path = request.uri().path().to_string();
     let score = detector.analyze(&request, &client_ip);
+    detector.record(&score, &request);
 
     if score.is_bot && !score.is_allowed_bot {
         tracing::warn!(
             client_ip = %client_ip,
             path = %path,
             score = score.score,
+            class = score.class.as_str(),
             signals = ?score.signals.iter().map(|s| s.name()).collect::<Vec<_>>(),
             "Bot detected"
         );
 
         if detector.config.block_bots {
+            detector.stats.write().blocked += 1;
             return (StatusCode::FORBIDDEN, "Access denied").into_response();
         }
     }
 
-    // Store score in extensions for downstream handlers
+    // Store score and class in extensions for downstream handlers
     let mut request = request;
+    request.extensions_mut().insert(score.class);
     request.extensions_mut().insert(score);
 
     next.run(request).await
 }
 
+/// First-party page view analytics
+///
+/// Runs inside [`bot_detection`] and reads the [`TrafficClass`] it attached,
+/// so only successful page views from human visitors are counted.
+pub async fn page_view_analytics(
+    State(detector): State<BotDetectionMiddleware>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    if !is_page_view(request.method().as_str(), request.uri().path()) {
+        return next.run(request).await;
+    }
+
+    let class = TrafficClass::of(&request);
+    let client_ip = detector
+        .client_ip(&request)
+        .map(|ip| ip.to_string())
+        .unwrap_or_default();
+
+    let response = next.run(request).await;
+    if response.status().is_success() {
+        detector.record_page_view(class, &client_ip);
+    }
+    response
+}
+
 /// Check honeypot fields in form data
 pub fn check_honeypot(
     form_data: &HashMap<String, String>,
@@ -520,6 +916,120 @@ mod tests {
             .any(|s| matches!(s, BotSignal::SuspiciousUserAgent(_))));
     }
 
+    #[test]
+    fn test_traffic_classification() {
+        let detector = BotDetectionMiddleware::new(BotDetectionConfig {
+            enable_timing_analysis: false,
+            known_bot_ips: vec!["66.249.64.0/19".to_string()],
+            suspected_bot_ips: vec!["203.0.113.7".to_string(), "not-an-ip".to_string()],
+            ..Default::default()
+        });
+        let browser = vec![
+            (
+                "user-agent",
+                "Mozilla/5.0 (X11; Linux x86_64) Firefox/120.0",
+            ),
+            ("accept", "text/html"),
+            ("accept-language", "en"),
+            ("accept-encoding", "gzip"),
+        ];
+
+        let human = detector.analyze(&create_request_with_headers(browser.clone()), "10.0.0.1");
+        assert_eq!(human.class, TrafficClass::Human);
+        assert!(!human.is_bot);
+
+        let crawler =
+            detector.analyze(&create_request_with_headers(browser.clone()), "66.249.66.1");
+        assert_eq!(crawler.class, TrafficClass::KnownBot);
+
+        let listed = detector.analyze(&create_request_with_headers(browser), "203.0.113.7");
+        assert_eq!(listed.class, TrafficClass::SuspectedBot);
+
+        let declared = detector.analyze(
+            &create_request_with_headers(vec![
+                (
+                    "user-agent",
+                    "ExampleCrawler/1.0 (+https://example.com/bot)",
+                ),
+                ("accept", "*/*"),
+                ("accept-language", "en"),
+                ("accept-encoding", "gzip"),
+            ]),
+            "10.0.0.2",
+        );
+        assert_eq!(declared.class, TrafficClass::KnownBot);
+        assert!(declared.is_bot);
+
+        let scripted = detector.analyze(&create_request_with_headers(vec![]), "10.0.0.3");
+        assert_eq!(scripted.class, TrafficClass::SuspectedBot);
+    }
+
+    #[test]
+    fn test_traffic_stats_exclude_bots_from_page_views() {
+        let detector = BotDetectionMiddleware::new(BotDetectionConfig {
+            enable_timing_analysis: false,
+            ..Default::default()
+        });
+        let page = |ua: &str, path: &str| {
+            Request::builder()
+                .uri(path)
+                .header("user-agent", ua)
+                .header("accept", "text/html")
+                .header("accept-language", "en")
+                .header("accept-encoding", "gzip")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        for (ua, path) in [
+            ("Mozilla/5.0 Firefox/120.0", "/hello-world"),
+            ("Mozilla/5.0 Firefox/120.0", "/api/v1/posts"),
+            ("Mozilla/5.0 Firefox/120.0", "/robots.txt"),
+            ("Mozilla/5.0 (compatible; Googlebot/2.1)", "/hello-world"),
+            ("Mozilla/5.0 (compatible; Googlebot/2.1)", "/about"),
+        ] {
+            let request = page(ua, path);
+            let score = detector.analyze(&request, "10.0.0.1");
+            detector.record(&score, &request);
+            if is_page_view("GET", path) {
+                detector.record_page_view(score.class, "10.0.0.1");
+            }
+        }
+
+        let stats = detector.stats();
+        assert_eq!(stats.requests[&TrafficClass::Human], 3);
+        assert_eq!(stats.requests[&TrafficClass::KnownBot], 2);
+        assert_eq!(stats.page_views, 1);
+        assert_eq!(stats.excluded_page_views, 2);
+        assert_eq!(stats.top_agents[0].requests, 2);
+        assert_eq!(stats.top_agents[0].class, TrafficClass::KnownBot);
+
+        detector.reset_stats();
+        assert!(detector.stats().requests.is_empty());
+    }
+
+    #[test]
+    fn test_analytics_pulse_reports_new_page_views() {
+        let detector = BotDetectionMiddleware::new(BotDetectionConfig::default());
+        let mut last = (0, 0);
+        assert!(detector.analytics_pulse(&mut last).is_none());
+
+        detector.record_page_view(TrafficClass::Human, "10.0.0.1");
+        detector.record_page_view(TrafficClass::Human, "10.0.0.1");
+        detector.record_page_view(TrafficClass::KnownBot, "10.0.0.2");
+        let pulse = detector.analytics_pulse(&mut last).unwrap();
+        assert_eq!(pulse.event_type, ANALYTICS_PULSE_EVENT);
+        assert_eq!(pulse.payload["page_views"], 2);
+        assert_eq!(pulse.payload["excluded_page_views"], 1);
+        assert!(detector.analytics_pulse(&mut last).is_none());
+
+        detector.reset_stats();
+        detector.record_page_view(TrafficClass::Human, "10.0.0.1");
+        let pulse = detector.analytics_pulse(&mut last).unwrap();
+        assert_eq!(pulse.payload["page_views"], 1);
+        assert_eq!(pulse.payload["total_page_views"], 1);
+    }
+
     #[test]
     fn test_honeypot_detection() {
         let mut form_data = HashMap::new();
@@ -16,6 +16,8 @@ use axum::{
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
+use crate::services::wordpress_import::{IMPORT_PATH, MAX_WXR_SIZE};
+
 /// Content security configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ContentSecurityConfig {
@@ -51,9 +53,13 @@ impl Default for ContentSecurityConfig {
                 "application/x-www-form-urlencoded".to_string(),
                 "multipart/form-data".to_string(),
                 "text/plain".to_string(),
+                // WordPress export files
+                "application/xml".to_string(),
+                "text/xml".to_string(),
             ],
             route_body_limits: vec![
                 ("/api/media/upload".to_string(), 100 * 1024 * 1024), // 100MB for uploads
+                (IMPORT_PATH.to_string(), MAX_WXR_SIZE),              // WordPress export files
                 ("/api/".to_string(), 10 * 1024 * 1024),              // 10MB for API
             ],
             default_max_body_size: 10 * 1024 * 1024, // 10MB
@@ -14,7 +14,9 @@ pub mod request_validation;
 pub mod security_audit;
 
 // Re-export commonly used types
-pub use bot_detection::{BotDetectionConfig, BotDetectionMiddleware, BotScore, BotSignal};
+pub use bot_detection::{
+    BotDetectionConfig, BotDetectionMiddleware, BotScore, BotSignal, BotTrafficStats, TrafficClass,
+};
 pub use content_security::{
     ContentSecurityConfig, ContentSecurityError, ContentSecurityMiddleware,
 };
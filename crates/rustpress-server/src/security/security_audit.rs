@@ -22,6 +22,8 @@ use std::net::IpAddr;
 use std::sync::Arc;
 use uuid::Uuid;
 
+use crate::services::GeoIpService;
+
 /// Types of security events
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum SecurityEvent {
@@ -137,6 +139,9 @@ pub struct SecurityEventRecord {
     pub method: Option<String>,
     /// Additional context
     pub context: Option<serde_json::Value>,
+    /// Client country, when GeoIP is enabled and storage is allowed
+    #[serde(default)]
+    pub country: Option<String>,
 }
 
 impl SecurityEventRecord {
@@ -151,6 +156,7 @@ impl SecurityEventRecord {
             path: None,
             method: None,
             context: None,
+            country: None,
         }
     }
 
@@ -205,6 +211,7 @@ impl Default for SecurityAuditConfig {
 pub struct SecurityAuditLogger {
     events: Arc<RwLock<VecDeque<SecurityEventRecord>>>,
     config: Arc<SecurityAuditConfig>,
+    geoip: Option<Arc<GeoIpService>>,
 }
 
 impl SecurityAuditLogger {
@@ -212,11 +219,33 @@ impl SecurityAuditLogger {
         Self {
             events: Arc::new(RwLock::new(VecDeque::with_capacity(config.max_events))),
             config: Arc::new(config),
+            geoip: None,
         }
     }
 
+    /// Locate client IPs and apply the GeoIP privacy settings to records
+    pub fn with_geoip(mut self, geoip: Arc<GeoIpService>) -> Self {
+        self.geoip = Some(geoip);
+        self
+    }
+
+    fn enrich(&self, record: &mut SecurityEventRecord) {
+        let Some(geoip) = &self.geoip else {
+            return;
+        };
+        let Some(ip) = record
+            .client_ip
+            .as_deref()
+            .and_then(|ip| ip.parse::<IpAddr>().ok())
+        else {
+            return;
+        };
+        record.country = geoip.country_for_storage(ip);
+        record.client_ip = Some(geoip.storage_ip(ip).to_string());
+    }
+
     /// Log a security event
-    pub fn log(&self, record: SecurityEventRecord) {
+    pub fn log(&self, mut record: SecurityEventRecord) {
         let severity = record.event.severity();
 
         // Check minimum severity
@@ -224,6 +253,8 @@ impl SecurityAuditLogger {
             return;
         }
 
+        self.enrich(&mut record);
+
         // Log to tracing if enabled
         if self.config.log_to_tracing {
             match severity {
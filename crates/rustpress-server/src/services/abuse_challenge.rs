@@ -0,0 +1,664 @@
+//! Anti-Abuse Challenges
+//!
+//! Cheap, self-hosted checks for endpoints that anonymous clients can post
+//! to (comments, registration, password reset, plugin forms), tried before
+//! reaching for an external CAPTCHA:
+//!
+//! - Honeypot fields that humans never see and bots tend to fill in
+//! - A time trap rejecting forms submitted too quickly or too late
+//! - An optional proof-of-work puzzle that makes bulk submissions expensive
+//!
+//! Each endpoint selects its own checks. Clients fetch a signed challenge
+//! before showing the form and send the answer back as `_challenge` in the
+//! JSON body.
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine as _;
+use chrono::Utc;
+use ring::hmac;
+use rustpress_cache::Cache;
+use rustpress_core::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+
+use super::json_setting::JsonSetting;
+use crate::security::bot_detection::check_honeypot;
+
+/// Settings key holding the challenge configuration
+pub const ABUSE_CHALLENGE_SETTINGS_KEY: &str = "abuse_challenges";
+
+/// Stored challenge settings
+const ABUSE_CHALLENGE_SETTING: JsonSetting<AbuseChallengeConfig> = JsonSetting::new(
+    ABUSE_CHALLENGE_SETTINGS_KEY,
+    "security",
+    "challenge settings",
+);
+
+/// Body field carrying the client's challenge answer
+pub const CHALLENGE_FIELD: &str = "_challenge";
+
+/// Built-in protected endpoints
+pub const COMMENT_ENDPOINT: &str = "comment";
+pub const REGISTRATION_ENDPOINT: &str = "registration";
+pub const PASSWORD_RESET_ENDPOINT: &str = "password_reset";
+
+/// Highest accepted proof-of-work difficulty (leading zero bits)
+const MAX_POW_DIFFICULTY: u8 = 24;
+
+/// Challenge settings for one endpoint
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EndpointChallenge {
+    /// Reject submissions that fill in a honeypot field
+    pub honeypot: bool,
+    /// Require a signed challenge token and check submission timing
+    pub time_trap: bool,
+    /// Minimum seconds between fetching the challenge and submitting
+    pub min_submit_secs: i64,
+    /// Seconds after which a challenge expires
+    pub max_age_secs: i64,
+    /// Leading zero bits required in the proof-of-work hash (0 disables it)
+    pub pow_difficulty: u8,
+}
+
+impl Default for EndpointChallenge {
+    fn default() -> Self {
+        Self {
+            honeypot: true,
+            time_trap: false,
+            min_submit_secs: 3,
+            max_age_secs: 7200,
+            pow_difficulty: 0,
+        }
+    }
+}
+
+impl EndpointChallenge {
+    /// Whether submissions need a challenge token
+    pub fn requires_token(&self) -> bool {
+        self.time_trap || self.pow_difficulty > 0
+    }
+}
+
+/// Anti-abuse challenge configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AbuseChallengeConfig {
+    pub enabled: bool,
+    /// Invisible field names rendered into protected forms
+    pub honeypot_fields: Vec<String>,
+    /// Settings per endpoint; endpoints not listed use the defaults
+    pub endpoints: HashMap<String, EndpointChallenge>,
+}
+
+impl Default for AbuseChallengeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            honeypot_fields: vec!["fax_number".to_string(), "company_site".to_string()],
+            endpoints: HashMap::new(),
+        }
+    }
+}
+
+impl AbuseChallengeConfig {
+    /// Load the configuration from settings, falling back to defaults
+    pub async fn load(pool: &PgPool) -> Result<Self> {
+        ABUSE_CHALLENGE_SETTING.load_or_default(pool).await
+    }
+
+    /// Persist the configuration to settings
+    pub async fn save(&self, pool: &PgPool) -> Result<()> {
+        ABUSE_CHALLENGE_SETTING.save(pool, self).await
+    }
+
+    /// Validate endpoint names, honeypot fields and limits
+    pub fn validate(&self) -> Result<()> {
+        for field in &self.honeypot_fields {
+            if field.is_empty() || field == CHALLENGE_FIELD {
+                return Err(Error::invalid_input(
+                    "honeypot_fields",
+                    format!("'{}' cannot be used as a honeypot field", field),
+                ));
+            }
+        }
+
+        for (endpoint, settings) in &self.endpoints {
+            if !is_valid_endpoint(endpoint) {
+                return Err(Error::invalid_input(
+                    "endpoints",
+                    format!("Invalid endpoint name '{}'", endpoint),
+                ));
+            }
+            if settings.pow_difficulty > MAX_POW_DIFFICULTY {
+                return Err(Error::invalid_input(
+                    "pow_difficulty",
+                    format!("Difficulty must be at most {}", MAX_POW_DIFFICULTY),
+                ));
+            }
+            if settings.min_submit_secs < 0 || settings.max_age_secs <= settings.min_submit_secs {
+                return Err(Error::invalid_input(
+                    "max_age_secs",
+                    format!(
+                        "Endpoint '{}' must allow submissions after min_submit_secs",
+                        endpoint
+                    ),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Settings for an endpoint
+    pub fn endpoint(&self, endpoint: &str) -> EndpointChallenge {
+        self.endpoints.get(endpoint).cloned().unwrap_or_default()
+    }
+}
+
+/// Endpoint names are lowercase identifiers, optionally namespaced (`form:contact`)
+pub fn is_valid_endpoint(endpoint: &str) -> bool {
+    !endpoint.is_empty()
+        && endpoint.len() <= 64
+        && endpoint
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '_' | '-' | ':'))
+}
+
+/// Proof-of-work puzzle sent to the client
+///
+/// The client must find a `nonce` such that `sha256("{token}:{nonce}")`
+/// starts with `difficulty` zero bits.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProofOfWork {
+    pub algorithm: &'static str,
+    pub difficulty: u8,
+}
+
+/// Challenge issued to a client before it renders a protected form
+#[derive(Debug, Clone, Serialize)]
+pub struct IssuedChallenge {
+    pub endpoint: String,
+    pub token: Option<String>,
+    /// Fields to render hidden and leave empty
+    pub honeypot_fields: Vec<String>,
+    pub min_submit_secs: Option<i64>,
+    pub expires_in_secs: Option<i64>,
+    pub proof_of_work: Option<ProofOfWork>,
+}
+
+/// Client answer sent as `_challenge` in the request body
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChallengeAnswer {
+    pub token: String,
+    #[serde(default)]
+    pub nonce: Option<String>,
+}
+
+/// Why a submission was rejected
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChallengeFailure {
+    HoneypotTriggered,
+    MissingToken,
+    InvalidToken,
+    TooFast,
+    Expired,
+    Replayed,
+    ProofOfWorkFailed,
+    /// Token use could not be recorded, so a replay cannot be ruled out
+    Unavailable,
+}
+
+impl ChallengeFailure {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChallengeFailure::HoneypotTriggered => "honeypot_triggered",
+            ChallengeFailure::MissingToken => "missing_token",
+            ChallengeFailure::InvalidToken => "invalid_token",
+            ChallengeFailure::TooFast => "too_fast",
+            ChallengeFailure::Expired => "expired",
+            ChallengeFailure::Replayed => "replayed",
+            ChallengeFailure::ProofOfWorkFailed => "proof_of_work_failed",
+            ChallengeFailure::Unavailable => "unavailable",
+        }
+    }
+}
+
+impl fmt::Display for ChallengeFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            // Deliberately vague so bots learn nothing from the honeypot
+            ChallengeFailure::HoneypotTriggered => "Submission rejected",
+            ChallengeFailure::MissingToken => "A challenge token is required",
+            ChallengeFailure::InvalidToken => "The challenge token is invalid",
+            ChallengeFailure::TooFast => "The form was submitted too quickly, please try again",
+            ChallengeFailure::Expired => "The challenge has expired, please reload the form",
+            ChallengeFailure::Replayed => "The challenge token was already used",
+            ChallengeFailure::ProofOfWorkFailed => "The proof-of-work answer is incorrect",
+            ChallengeFailure::Unavailable => "The challenge could not be checked, please retry",
+        };
+        f.write_str(message)
+    }
+}
+
+/// Signed token contents: `{endpoint}.{issued_at}.{salt}.{signature}`
+struct TokenClaims<'a> {
+    endpoint: &'a str,
+    issued_at: i64,
+    signature: &'a str,
+}
+
+/// Issues and verifies anti-abuse challenges
+pub struct AbuseChallengeService {
+    pool: PgPool,
+    key: hmac::Key,
+    config: RwLock<Option<Arc<AbuseChallengeConfig>>>,
+    /// Shared cache recording consumed token signatures until they expire,
+    /// so a token is only accepted once across all instances
+    cache: Arc<Cache>,
+}
+
+impl AbuseChallengeService {
+    /// Create the service; tokens are signed with a key derived from `secret`
+    pub fn new(pool: PgPool, cache: Arc<Cache>, secret: &str) -> Self {
+        Self {
+            pool,
+            key: hmac::Key::new(
+                hmac::HMAC_SHA256,
+                format!("abuse-challenge:{}", secret).as_bytes(),
+            ),
+            config: RwLock::new(None),
+            cache,
+        }
+    }
+
+    /// Challenge configuration, loaded from settings on first use
+    pub async fn config(&self) -> Arc<AbuseChallengeConfig> {
+        if let Some(config) = self.config.read().await.clone() {
+            return config;
+        }
+
+        match AbuseChallengeConfig::load(&self.pool).await {
+            Ok(config) => {
+                let config = Arc::new(config);
+                *self.config.write().await = Some(config.clone());
+                config
+            }
+            Err(e) => {
+                tracing::warn!("Failed to load challenge settings, using defaults: {}", e);
+                Arc::new(AbuseChallengeConfig::default())
+            }
+        }
+    }
+
+    /// Replace the cached configuration after it was saved
+    pub async fn set_config(&self, config: AbuseChallengeConfig) {
+        *self.config.write().await = Some(Arc::new(config));
+    }
+
+    /// Drop the cached configuration; the next use reloads it
+    pub async fn invalidate(&self) {
+        *self.config.write().await = None;
+    }
+
+    /// Issue a challenge for an endpoint
+    pub async fn issue(&self, endpoint: &str) -> Result<IssuedChallenge> {
+        if !is_valid_endpoint(endpoint) {
+            return Err(Error::invalid_input("endpoint", "Invalid endpoint name"));
+        }
+        let config = self.config().await;
+        Ok(self.issue_at(&config, endpoint, Utc::now().timestamp()))
+    }
+
+    /// Check a submission against the endpoint's challenges
+    pub async fn verify(
+        &self,
+        endpoint: &str,
+        body: &Value,
+    ) -> std::result::Result<(), ChallengeFailure> {
+        let config = self.config().await;
+        let result = self
+            .verify_at(&config, endpoint, body, Utc::now().timestamp())
+            .await;
+        if let Err(failure) = result {
+            tracing::info!(
+                endpoint = %endpoint,
+                reason = failure.as_str(),
+                "Rejected submission failing anti-abuse challenge"
+            );
+        }
+        result
+    }
+
+    fn issue_at(&self, config: &AbuseChallengeConfig, endpoint: &str, now: i64) -> IssuedChallenge {
+        let settings = config.endpoint(endpoint);
+        let active = config.enabled;
+
+        let token = (active && settings.requires_token()).then(|| {
+            let payload = format!("{}.{}.{}", endpoint, now, uuid::Uuid::new_v4().simple());
+            format!("{}.{}", payload, self.sign(&payload))
+        });
+
+        IssuedChallenge {
+            endpoint: endpoint.to_string(),
+            honeypot_fields: if active && settings.honeypot {
+                config.honeypot_fields.clone()
+            } else {
+                Vec::new()
+            },
+            min_submit_secs: (active && settings.time_trap).then_some(settings.min_submit_secs),
+            expires_in_secs: token.as_ref().map(|_| settings.max_age_secs),
+            proof_of_work: (active && settings.pow_difficulty > 0).then_some(ProofOfWork {
+                algorithm: "sha256",
+                difficulty: settings.pow_difficulty,
+            }),
+            token,
+        }
+    }
+
+    async fn verify_at(
+        &self,
+        config: &AbuseChallengeConfig,
+        endpoint: &str,
+        body: &Value,
+        now: i64,
+    ) -> std::result::Result<(), ChallengeFailure> {
+        if !config.enabled {
+            return Ok(());
+        }
+        let settings = config.endpoint(endpoint);
+
+        if settings.honeypot {
+            let filled: HashMap<String, String> = config
+                .honeypot_fields
+                .iter()
+                .filter_map(|field| {
+                    let value = match body.get(field)? {
+                        Value::Null => String::new(),
+                        Value::String(s) => s.clone(),
+                        other => other.to_string(),
+                    };
+                    Some((field.clone(), value))
+                })
+                .collect();
+            let fields: Vec<&str> = config.honeypot_fields.iter().map(String::as_str).collect();
+            if check_honeypot(&filled, &fields).is_some() {
+                return Err(ChallengeFailure::HoneypotTriggered);
+            }
+        }
+
+        if !settings.requires_token() {
+            return Ok(());
+        }
+
+        let answer: ChallengeAnswer = body
+            .get(CHALLENGE_FIELD)
+            .cloned()
+            .and_then(|v| serde_json::from_value(v).ok())
+            .ok_or(ChallengeFailure::MissingToken)?;
+        let claims = self
+            .parse_token(&answer.token)
+            .ok_or(ChallengeFailure::InvalidToken)?;
+        if claims.endpoint != endpoint {
+            return Err(ChallengeFailure::InvalidToken);
+        }
+
+        let age = now - claims.issued_at;
+        if age > settings.max_age_secs {
+            return Err(ChallengeFailure::Expired);
+        }
+        if settings.time_trap && age < settings.min_submit_secs {
+            return Err(ChallengeFailure::TooFast);
+        }
+
+        if settings.pow_difficulty > 0 {
+            let nonce = answer
+                .nonce
+                .as_deref()
+                .ok_or(ChallengeFailure::ProofOfWorkFailed)?;
+            if !verify_proof_of_work(&answer.token, nonce, settings.pow_difficulty) {
+                return Err(ChallengeFailure::ProofOfWorkFailed);
+            }
+        }
+
+        // Consume the token last so a too-early submission can be retried
+        let remaining = (claims.issued_at + settings.max_age_secs - now).max(1) as u64;
+        let uses = self
+            .cache
+            .increment_window(
+                format!("abuse_token:{}", claims.signature),
+                1,
+                Duration::from_secs(remaining),
+            )
+            .await
+            .map_err(|e| {
+                tracing::warn!("Failed to record challenge token use: {}", e);
+                ChallengeFailure::Unavailable
+            })?;
+        if uses > 1 {
+            return Err(ChallengeFailure::Replayed);
+        }
+
+        Ok(())
+    }
+
+    fn sign(&self, payload: &str) -> String {
+        URL_SAFE_NO_PAD.encode(hmac::sign(&self.key, payload.as_bytes()))
+    }
+
+    fn parse_token<'a>(&self, token: &'a str) -> Option<TokenClaims<'a>> {
+        let (payload, signature) = token.rsplit_once('.')?;
+        let tag = URL_SAFE_NO_PAD.decode(signature).ok()?;
+        hmac::verify(&self.key, payload.as_bytes(), &tag).ok()?;
+        let mut parts = payload.split('.');
+        let endpoint = parts.next()?;
+        let issued_at = parts.next()?.parse().ok()?;
+        parts.next()?;
+        if parts.next().is_some() {
+            return None;
+        }
+        Some(TokenClaims {
+            endpoint,
+            issued_at,
+            signature,
+        })
+    }
+}
+
+/// Whether `sha256("{token}:{nonce}")` starts with `difficulty` zero bits
+pub fn verify_proof_of_work(token: &str, nonce: &str, difficulty: u8) -> bool {
+    if nonce.len() > 64 {
+        return false;
+    }
+    let digest = Sha256::digest(format!("{}:{}", token, nonce).as_bytes());
+    leading_zero_bits(&digest) >= u32::from(difficulty)
+}
+
+fn leading_zero_bits(bytes: &[u8]) -> u32 {
+    let mut bits = 0;
+    for byte in bytes {
+        if *byte == 0 {
+            bits += 8;
+        } else {
+            bits += byte.leading_zeros();
+            break;
+        }
+    }
+    bits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustpress_cache::MemoryBackend;
+    use serde_json::json;
+
+    fn service() -> AbuseChallengeService {
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .connect_lazy("postgres://localhost/rustpress_test")
+            .unwrap();
+        let cache = Arc::new(Cache::new(Arc::new(MemoryBackend::new(1_000))));
+        AbuseChallengeService::new(pool, cache, "test-secret")
+    }
+
+    fn strict_config() -> AbuseChallengeConfig {
+        AbuseChallengeConfig {
+            endpoints: HashMap::from([(
+                COMMENT_ENDPOINT.to_string(),
+                EndpointChallenge {
+                    time_trap: true,
+                    pow_difficulty: 8,
+                    ..Default::default()
+                },
+            )]),
+            ..Default::default()
+        }
+    }
+
+    fn solve(token: &str, difficulty: u8) -> String {
+        (0u64..)
+            .map(|n| n.to_string())
+            .find(|nonce| verify_proof_of_work(token, nonce, difficulty))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_honeypot_only_by_default() {
+        let service = service();
+        let config = AbuseChallengeConfig::default();
+
+        let clean = json!({ "content": "Nice post", "fax_number": "" });
+        assert_eq!(
+            service
+                .verify_at(&config, COMMENT_ENDPOINT, &clean, 0)
+                .await,
+            Ok(())
+        );
+
+        let filled = json!({ "content": "Buy now", "company_site": "http://spam" });
+        assert_eq!(
+            service
+                .verify_at(&config, COMMENT_ENDPOINT, &filled, 0)
+                .await,
+            Err(ChallengeFailure::HoneypotTriggered)
+        );
+
+        let issued = service.issue_at(&config, COMMENT_ENDPOINT, 0);
+        assert!(issued.token.is_none());
+        assert_eq!(issued.honeypot_fields.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_time_trap_and_proof_of_work() {
+        let service = service();
+        let config = strict_config();
+        let issued = service.issue_at(&config, COMMENT_ENDPOINT, 1_000);
+        let token = issued.token.unwrap();
+        let nonce = solve(&token, 8);
+        let body = json!({ "_challenge": { "token": token, "nonce": nonce } });
+
+        assert_eq!(
+            service
+                .verify_at(&config, COMMENT_ENDPOINT, &body, 1_001)
+                .await,
+            Err(ChallengeFailure::TooFast)
+        );
+        assert_eq!(
+            service
+                .verify_at(&config, COMMENT_ENDPOINT, &body, 10_000)
+                .await,
+            Err(ChallengeFailure::Expired)
+        );
+        assert_eq!(
+            service
+                .verify_at(&config, REGISTRATION_ENDPOINT, &body, 1_010)
+                .await,
+            Ok(()),
+            "endpoints without a token requirement only check honeypots"
+        );
+
+        let wrong = json!({ "_challenge": { "token": token, "nonce": "x" } });
+        if !verify_proof_of_work(&token, "x", 8) {
+            assert_eq!(
+                service
+                    .verify_at(&config, COMMENT_ENDPOINT, &wrong, 1_010)
+                    .await,
+                Err(ChallengeFailure::ProofOfWorkFailed)
+            );
+        }
+
+        assert_eq!(
+            service
+                .verify_at(&config, COMMENT_ENDPOINT, &body, 1_010)
+                .await,
+            Ok(())
+        );
+        assert_eq!(
+            service
+                .verify_at(&config, COMMENT_ENDPOINT, &body, 1_011)
+                .await,
+            Err(ChallengeFailure::Replayed)
+        );
+
+        // Another instance sharing the cache sees the token as used too
+        let peer =
+            AbuseChallengeService::new(service.pool.clone(), service.cache.clone(), "test-secret");
+        assert_eq!(
+            peer.verify_at(&config, COMMENT_ENDPOINT, &body, 1_012)
+                .await,
+            Err(ChallengeFailure::Replayed)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rejects_forged_and_foreign_tokens() {
+        let service = service();
+        let config = strict_config();
+        assert_eq!(
+            service
+                .verify_at(&config, COMMENT_ENDPOINT, &json!({}), 0)
+                .await,
+            Err(ChallengeFailure::MissingToken)
+        );
+
+        let token = service
+            .issue_at(&config, COMMENT_ENDPOINT, 0)
+            .token
+            .unwrap();
+        let forged = token.replacen(".0.", ".-100.", 1);
+        let body = json!({ "_challenge": { "token": forged } });
+        assert_eq!(
+            service
+                .verify_at(&config, COMMENT_ENDPOINT, &body, 10)
+                .await,
+            Err(ChallengeFailure::InvalidToken)
+        );
+
+        let other =
+            AbuseChallengeService::new(service.pool.clone(), service.cache.clone(), "other-secret");
+        let foreign = other.issue_at(&config, COMMENT_ENDPOINT, 0).token.unwrap();
+        let body = json!({ "_challenge": { "token": foreign } });
+        assert_eq!(
+            service
+                .verify_at(&config, COMMENT_ENDPOINT, &body, 10)
+                .await,
+            Err(ChallengeFailure::InvalidToken)
+        );
+
+        let mut invalid = strict_config();
+        invalid
+            .endpoints
+            .get_mut(COMMENT_ENDPOINT)
+            .unwrap()
+            .pow_difficulty = 40;
+        assert!(invalid.validate().is_err());
+        assert!(strict_config().validate().is_ok());
+    }
+}
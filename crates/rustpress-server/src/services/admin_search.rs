@@ -0,0 +1,1037 @@
+//! Admin Search
+//!
+//! Type-ahead search behind the admin command palette. One query looks
+//! through posts, pages, media, users, settings and the admin screens
+//! themselves (core screens plus the pages active plugins declare in their
+//! manifests) and answers with deep links into the admin UI.
+//!
+//! Matching is by prefix on titles, names and keys, which the indexes from
+//! `00031_admin_search_indexes.sql` serve; substring matches are added once
+//! the term is long enough to be selective. Database hits are cached per
+//! user and query for a few seconds so each keystroke of a palette session
+//! does not hit the database again.
+//!
+//! Trashed posts, pages, media and users are found too, flagged and ranked
+//! after live ones, and link to the trash view of their list. Results are
+//! filtered by role: contributors and authors only see their own content,
+//! pages and comments-level screens need an editor, and users, settings and
+//! site configuration need an administrator.
+
+use parking_lot::{Mutex, RwLock};
+use rustpress_core::error::{Error, Result};
+use rustpress_core::plugin_loader::PluginManifest;
+use rustpress_core::tenant::site_filter;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// Longest accepted search term
+pub const MAX_TERM_LENGTH: usize = 100;
+
+/// Hits returned per kind when the caller does not ask for a number
+pub const DEFAULT_HITS_PER_KIND: usize = 5;
+
+/// Most hits returned per kind
+pub const MAX_HITS_PER_KIND: usize = 20;
+
+/// Terms shorter than this only match by prefix
+const SUBSTRING_MIN_LENGTH: usize = 3;
+
+/// How long database hits are reused for the same user and query
+const SEARCH_CACHE_TTL: Duration = Duration::from_secs(15);
+
+/// Cached queries kept before expired ones are dropped
+const SEARCH_CACHE_CAPACITY: usize = 1024;
+
+/// Settings groups with their own screen under `/settings/{group}`
+const SETTINGS_SCREENS: [&str; 10] = [
+    "writing",
+    "reading",
+    "discussion",
+    "media",
+    "permalinks",
+    "privacy",
+    "storage",
+    "subscription",
+    "site-mode",
+    "preloader",
+];
+
+/// Core admin screens: path, title, required role and extra search words
+const CORE_SCREENS: &[(&str, &str, AdminRole, &[&str])] = &[
+    (
+        "dashboard",
+        "Dashboard",
+        AdminRole::Subscriber,
+        &["home", "overview"],
+    ),
+    ("posts", "Posts", AdminRole::Contributor, &["articles"]),
+    (
+        "posts/new",
+        "New post",
+        AdminRole::Contributor,
+        &["add", "write", "create"],
+    ),
+    ("pages", "Pages", AdminRole::Editor, &[]),
+    (
+        "pages/new",
+        "New page",
+        AdminRole::Editor,
+        &["add", "create"],
+    ),
+    (
+        "media",
+        "Media library",
+        AdminRole::Author,
+        &["uploads", "images", "files"],
+    ),
+    (
+        "comments",
+        "Comments",
+        AdminRole::Editor,
+        &["moderation", "discussion"],
+    ),
+    ("categories", "Categories", AdminRole::Editor, &["taxonomy"]),
+    ("tags", "Tags", AdminRole::Editor, &["taxonomy"]),
+    ("menus", "Menus", AdminRole::Administrator, &["navigation"]),
+    (
+        "themes",
+        "Themes",
+        AdminRole::Administrator,
+        &["appearance", "design"],
+    ),
+    ("widgets", "Widgets", AdminRole::Administrator, &["sidebar"]),
+    (
+        "appearance",
+        "Appearance",
+        AdminRole::Administrator,
+        &["customize"],
+    ),
+    (
+        "plugins",
+        "Plugins",
+        AdminRole::Administrator,
+        &["extensions", "addons"],
+    ),
+    (
+        "users",
+        "Users",
+        AdminRole::Administrator,
+        &["accounts", "people"],
+    ),
+    (
+        "roles",
+        "Roles",
+        AdminRole::Administrator,
+        &["permissions", "capabilities"],
+    ),
+    (
+        "analytics",
+        "Analytics",
+        AdminRole::Administrator,
+        &["stats", "traffic"],
+    ),
+    (
+        "seo",
+        "SEO",
+        AdminRole::Administrator,
+        &["search engine", "sitemap"],
+    ),
+    (
+        "cache",
+        "Cache",
+        AdminRole::Administrator,
+        &["purge", "performance"],
+    ),
+    (
+        "database",
+        "Database",
+        AdminRole::Administrator,
+        &["tables", "sql"],
+    ),
+    (
+        "settings",
+        "General settings",
+        AdminRole::Administrator,
+        &["options", "site title"],
+    ),
+    (
+        "settings/writing",
+        "Writing settings",
+        AdminRole::Administrator,
+        &["editor"],
+    ),
+    (
+        "settings/reading",
+        "Reading settings",
+        AdminRole::Administrator,
+        &["homepage", "front page"],
+    ),
+    (
+        "settings/discussion",
+        "Discussion settings",
+        AdminRole::Administrator,
+        &["comments"],
+    ),
+    (
+        "settings/media",
+        "Media settings",
+        AdminRole::Administrator,
+        &["image sizes", "thumbnails"],
+    ),
+    (
+        "settings/permalinks",
+        "Permalink settings",
+        AdminRole::Administrator,
+        &["urls", "slugs"],
+    ),
+    (
+        "settings/privacy",
+        "Privacy settings",
+        AdminRole::Administrator,
+        &["gdpr", "cookies"],
+    ),
+    (
+        "settings/storage",
+        "Storage settings",
+        AdminRole::Administrator,
+        &["s3", "uploads"],
+    ),
+    (
+        "settings/subscription",
+        "Subscription settings",
+        AdminRole::Administrator,
+        &["newsletter"],
+    ),
+    (
+        "settings/site-mode",
+        "Site mode",
+        AdminRole::Administrator,
+        &["maintenance", "coming soon"],
+    ),
+    (
+        "settings/preloader",
+        "Preloader settings",
+        AdminRole::Administrator,
+        &["loading"],
+    ),
+];
+
+/// Role ladder used to filter results; each role includes the ones below
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AdminRole {
+    Subscriber,
+    Contributor,
+    Author,
+    Editor,
+    Administrator,
+}
+
+impl AdminRole {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "subscriber" => Some(Self::Subscriber),
+            "contributor" => Some(Self::Contributor),
+            "author" => Some(Self::Author),
+            "editor" => Some(Self::Editor),
+            "administrator" | "super_admin" => Some(Self::Administrator),
+            _ => None,
+        }
+    }
+
+    /// Lowest role holding a WordPress-style capability; unknown or missing
+    /// capabilities are kept for administrators
+    pub fn for_capability(capability: Option<&str>) -> Self {
+        match capability {
+            Some("read") => Self::Subscriber,
+            Some("edit_posts") => Self::Contributor,
+            Some("upload_files" | "publish_posts" | "edit_published_posts") => Self::Author,
+            Some(
+                "edit_pages" | "edit_others_posts" | "publish_pages" | "moderate_comments"
+                | "manage_categories",
+            ) => Self::Editor,
+            _ => Self::Administrator,
+        }
+    }
+}
+
+/// What a search hit points at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchKind {
+    /// A core or plugin admin screen
+    Screen,
+    Post,
+    Page,
+    Media,
+    User,
+    Setting,
+}
+
+impl SearchKind {
+    /// Every kind, in the order hits are returned
+    pub const ALL: [SearchKind; 6] = [
+        Self::Screen,
+        Self::Post,
+        Self::Page,
+        Self::Media,
+        Self::User,
+        Self::Setting,
+    ];
+}
+
+impl FromStr for SearchKind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "screen" => Ok(Self::Screen),
+            "post" => Ok(Self::Post),
+            "page" => Ok(Self::Page),
+            "media" => Ok(Self::Media),
+            "user" => Ok(Self::User),
+            "setting" => Ok(Self::Setting),
+            _ => Err(Error::invalid_input(
+                "types",
+                format!(
+                    "Unknown type '{}'; expected any of: screen, post, page, media, user, setting",
+                    s
+                ),
+            )),
+        }
+    }
+}
+
+/// Whether trashed content is searched
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrashFilter {
+    /// Live and trashed content, trashed ranked last
+    #[default]
+    Include,
+    /// Live content only
+    Exclude,
+    /// Trashed content only
+    Only,
+}
+
+impl TrashFilter {
+    fn live(&self) -> bool {
+        *self != Self::Only
+    }
+
+    fn trashed(&self) -> bool {
+        *self != Self::Exclude
+    }
+}
+
+/// A validated palette query
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AdminSearchQuery {
+    /// Lowercased, trimmed search term
+    pub term: String,
+    pub kinds: Vec<SearchKind>,
+    pub trash: TrashFilter,
+    pub limit: usize,
+}
+
+impl AdminSearchQuery {
+    /// Parse the raw term and comma-separated kinds; an empty `types`
+    /// searches every kind
+    pub fn new(
+        q: &str,
+        types: Option<&str>,
+        trash: TrashFilter,
+        limit: Option<usize>,
+    ) -> Result<Self> {
+        let term = q.split_whitespace().collect::<Vec<_>>().join(" ");
+        if term.is_empty() {
+            return Err(Error::invalid_input("q", "Search term is required"));
+        }
+        if term.chars().count() > MAX_TERM_LENGTH {
+            return Err(Error::invalid_input(
+                "q",
+                format!("Must be at most {} characters", MAX_TERM_LENGTH),
+            ));
+        }
+
+        let mut kinds = Vec::new();
+        for kind in types.unwrap_or_default().split(',') {
+            let kind = kind.trim();
+            if !kind.is_empty() {
+                kinds.push(kind.parse::<SearchKind>()?);
+            }
+        }
+        let kinds = if kinds.is_empty() {
+            SearchKind::ALL.to_vec()
+        } else {
+            SearchKind::ALL
+                .into_iter()
+                .filter(|kind| kinds.contains(kind))
+                .collect()
+        };
+
+        Ok(Self {
+            term: term.to_lowercase(),
+            kinds,
+            trash,
+            limit: limit
+                .unwrap_or(DEFAULT_HITS_PER_KIND)
+                .clamp(1, MAX_HITS_PER_KIND),
+        })
+    }
+
+    fn wants(&self, kind: SearchKind) -> bool {
+        self.kinds.contains(&kind)
+    }
+
+    /// `LIKE` pattern matching the term at the start of a value
+    fn prefix_pattern(&self) -> String {
+        format!("{}%", like_escape(&self.term))
+    }
+
+    /// `LIKE` pattern for substring matches; the prefix pattern while the
+    /// term is too short to be selective
+    fn substring_pattern(&self) -> String {
+        if self.term.chars().count() < SUBSTRING_MIN_LENGTH {
+            self.prefix_pattern()
+        } else {
+            format!("%{}%", like_escape(&self.term))
+        }
+    }
+}
+
+/// One palette entry
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AdminSearchHit {
+    pub kind: SearchKind,
+    /// Entity id, setting key or screen id
+    pub id: String,
+    pub title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subtitle: Option<String>,
+    /// Admin UI path to jump to
+    pub url: String,
+    pub trashed: bool,
+}
+
+/// Palette hits grouped by kind
+#[derive(Debug, Clone, Serialize)]
+pub struct AdminSearchResults {
+    pub term: String,
+    pub hits: Vec<AdminSearchHit>,
+}
+
+/// The user a search runs for
+#[derive(Debug, Clone)]
+pub struct SearchUser {
+    pub id: Uuid,
+    pub roles: Vec<String>,
+}
+
+impl SearchUser {
+    pub fn new(id: Uuid, roles: Vec<String>) -> Self {
+        Self { id, roles }
+    }
+
+    /// Highest role held; users without a known role rank as subscribers
+    fn role(&self) -> AdminRole {
+        self.roles
+            .iter()
+            .filter_map(|role| AdminRole::from_name(role))
+            .max()
+            .unwrap_or(AdminRole::Subscriber)
+    }
+
+    /// Author to restrict content to, or `None` when the user sees everyone's
+    fn own_content_only(&self) -> Option<Uuid> {
+        (self.role() < AdminRole::Editor).then_some(self.id)
+    }
+}
+
+/// A screen the palette can jump to
+#[derive(Debug, Clone)]
+struct AdminScreen {
+    id: String,
+    title: String,
+    subtitle: String,
+    url: String,
+    keywords: Vec<String>,
+    role: AdminRole,
+    /// Plugin that declared the screen; core screens have none
+    plugin: Option<String>,
+}
+
+impl AdminScreen {
+    /// How well the screen matches: title prefix, word prefix, then keyword
+    fn rank(&self, term: &str) -> Option<u8> {
+        let title = self.title.to_lowercase();
+        if title.starts_with(term) {
+            Some(0)
+        } else if title.split_whitespace().any(|word| word.starts_with(term)) {
+            Some(1)
+        } else if self
+            .keywords
+            .iter()
+            .any(|keyword| keyword.starts_with(term))
+        {
+            Some(2)
+        } else {
+            None
+        }
+    }
+
+    fn hit(&self) -> AdminSearchHit {
+        AdminSearchHit {
+            kind: SearchKind::Screen,
+            id: self.id.clone(),
+            title: self.title.clone(),
+            subtitle: Some(self.subtitle.clone()),
+            url: self.url.clone(),
+            trashed: false,
+        }
+    }
+}
+
+fn core_screens() -> Vec<AdminScreen> {
+    CORE_SCREENS
+        .iter()
+        .map(|(path, title, role, keywords)| AdminScreen {
+            id: path.replace('/', "."),
+            title: title.to_string(),
+            subtitle: if path.starts_with("settings") {
+                "Settings".to_string()
+            } else {
+                "Admin".to_string()
+            },
+            url: format!("/{}", path),
+            keywords: keywords.iter().map(|k| k.to_string()).collect(),
+            role: *role,
+            plugin: None,
+        })
+        .collect()
+}
+
+#[derive(Debug, FromRow)]
+struct EntityRow {
+    id: Uuid,
+    title: String,
+    subtitle: Option<String>,
+    trashed: bool,
+}
+
+#[derive(Debug, FromRow)]
+struct SettingRow {
+    key: String,
+    group_name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct CacheKey {
+    user_id: Uuid,
+    role: AdminRole,
+    query: AdminSearchQuery,
+}
+
+/// Command palette search across admin entities and screens
+pub struct AdminSearchService {
+    pool: PgPool,
+    screens: RwLock<Vec<AdminScreen>>,
+    cache: Mutex<HashMap<CacheKey, (Instant, Vec<AdminSearchHit>)>>,
+}
+
+impl AdminSearchService {
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            screens: RwLock::new(core_screens()),
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Make the admin pages declared in plugin manifests searchable,
+    /// replacing any registered before; they only show up while the plugin
+    /// is active
+    pub fn register_plugin_pages(&self, manifests: &[PluginManifest]) {
+        let mut screens = core_screens();
+        for manifest in manifests {
+            for page in &manifest.admin.pages {
+                screens.push(AdminScreen {
+                    id: format!("{}.{}", manifest.id, page.id),
+                    title: page.title.clone(),
+                    subtitle: manifest.name.clone(),
+                    url: format!("/plugins/{}/{}", manifest.id, page.id),
+                    keywords: vec![manifest.name.to_lowercase()],
+                    role: AdminRole::for_capability(page.capability.as_deref()),
+                    plugin: Some(manifest.id.clone()),
+                });
+            }
+        }
+        *self.screens.write() = screens;
+    }
+
+    /// Search everything the user may see; `active_plugins` holds the ids
+    /// of plugins whose screens are offered
+    pub async fn search(
+        &self,
+        user: &SearchUser,
+        query: &AdminSearchQuery,
+        active_plugins: &HashSet<String>,
+    ) -> Result<AdminSearchResults> {
+        let mut hits = if query.wants(SearchKind::Screen) && query.trash.live() {
+            self.screen_hits(user, query, active_plugins)
+        } else {
+            Vec::new()
+        };
+        hits.extend(self.entity_hits(user, query).await?);
+
+        Ok(AdminSearchResults {
+            term: query.term.clone(),
+            hits,
+        })
+    }
+
+    fn screen_hits(
+        &self,
+        user: &SearchUser,
+        query: &AdminSearchQuery,
+        active_plugins: &HashSet<String>,
+    ) -> Vec<AdminSearchHit> {
+        let role = user.role();
+        let screens = self.screens.read();
+        let mut matches: Vec<(u8, &AdminScreen)> = screens
+            .iter()
+            .filter(|screen| screen.role <= role)
+            .filter(|screen| {
+                screen
+                    .plugin
+                    .as_ref()
+                    .is_none_or(|plugin| active_plugins.contains(plugin))
+            })
+            .filter_map(|screen| screen.rank(&query.term).map(|rank| (rank, screen)))
+            .collect();
+        matches.sort_by(|(a_rank, a), (b_rank, b)| {
+            a_rank.cmp(b_rank).then_with(|| a.title.cmp(&b.title))
+        });
+
+        matches
+            .into_iter()
+            .take(query.limit)
+            .map(|(_, screen)| screen.hit())
+            .collect()
+    }
+
+    /// Post, page, media, user and setting hits, cached briefly per user
+    async fn entity_hits(
+        &self,
+        user: &SearchUser,
+        query: &AdminSearchQuery,
+    ) -> Result<Vec<AdminSearchHit>> {
+        let key = CacheKey {
+            user_id: user.id,
+            role: user.role(),
+            query: query.clone(),
+        };
+        if let Some((fetched_at, hits)) = self.cache.lock().get(&key) {
+            if fetched_at.elapsed() < SEARCH_CACHE_TTL {
+                return Ok(hits.clone());
+            }
+        }
+
+        let (posts, pages, media, users, settings) = tokio::try_join!(
+            self.post_hits(user, query, SearchKind::Post),
+            self.post_hits(user, query, SearchKind::Page),
+            self.media_hits(user, query),
+            self.user_hits(user, query),
+            self.setting_hits(user, query),
+        )?;
+        let hits: Vec<AdminSearchHit> = posts
+            .into_iter()
+            .chain(pages)
+            .chain(media)
+            .chain(users)
+            .chain(settings)
+            .collect();
+
+        let mut cache = self.cache.lock();
+        if cache.len() >= SEARCH_CACHE_CAPACITY {
+            cache.retain(|_, (fetched_at, _)| fetched_at.elapsed() < SEARCH_CACHE_TTL);
+            if cache.len() >= SEARCH_CACHE_CAPACITY {
+                cache.clear();
+            }
+        }
+        cache.insert(key, (Instant::now(), hits.clone()));
+        Ok(hits)
+    }
+
+    /// Posts or pages; pages need an editor, posts are limited to the
+    /// user's own below editor
+    async fn post_hits(
+        &self,
+        user: &SearchUser,
+        query: &AdminSearchQuery,
+        kind: SearchKind,
+    ) -> Result<Vec<AdminSearchHit>> {
+        let (post_type, required) = match kind {
+            SearchKind::Page => ("page", AdminRole::Editor),
+            _ => ("post", AdminRole::Contributor),
+        };
+        if !query.wants(kind) || user.role() < required {
+            return Ok(Vec::new());
+        }
+
+        let rows: Vec<EntityRow> = sqlx::query_as(&format!(
+            r#"
+            SELECT id, title, status AS subtitle,
+                   (deleted_at IS NOT NULL OR status = 'trash') AS trashed
+            FROM posts
+            WHERE post_type = $1
+              AND (lower(title) LIKE $2 OR lower(title) LIKE $3)
+              AND ($4::uuid IS NULL OR author_id = $4)
+              AND (($5 AND deleted_at IS NULL AND status <> 'trash')
+                OR ($6 AND (deleted_at IS NOT NULL OR status = 'trash')))
+              AND {site}
+            ORDER BY lower(title) LIKE $2 DESC, trashed, updated_at DESC
+            LIMIT $7
+            "#,
+            site = site_filter("site_id")
+        ))
+        .bind(post_type)
+        .bind(query.prefix_pattern())
+        .bind(query.substring_pattern())
+        .bind(user.own_content_only())
+        .bind(query.trash.live())
+        .bind(query.trash.trashed())
+        .bind(query.limit as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to search posts", e))?;
+
+        let list = if kind == SearchKind::Page {
+            "pages"
+        } else {
+            "posts"
+        };
+        Ok(rows
+            .into_iter()
+            .map(|row| AdminSearchHit {
+                kind,
+                id: row.id.to_string(),
+                url: if row.trashed {
+                    trash_url(list, row.id)
+                } else {
+                    format!("/{}/{}/edit", list, row.id)
+                },
+                title: row.title,
+                subtitle: row.subtitle,
+                trashed: row.trashed,
+            })
+            .collect())
+    }
+
+    /// Media by title or original file name; limited to the user's own
+    /// uploads below editor
+    async fn media_hits(
+        &self,
+        user: &SearchUser,
+        query: &AdminSearchQuery,
+    ) -> Result<Vec<AdminSearchHit>> {
+        if !query.wants(SearchKind::Media) || user.role() < AdminRole::Author {
+            return Ok(Vec::new());
+        }
+
+        let rows: Vec<EntityRow> = sqlx::query_as(&format!(
+            r#"
+            SELECT id, COALESCE(NULLIF(title, ''), original_filename) AS title,
+                   mime_type AS subtitle, deleted_at IS NOT NULL AS trashed
+            FROM media
+            WHERE (lower(title) LIKE $1 OR lower(original_filename) LIKE $1
+                OR lower(title) LIKE $2 OR lower(original_filename) LIKE $2)
+              AND ($3::uuid IS NULL OR uploaded_by = $3)
+              AND (($4 AND deleted_at IS NULL) OR ($5 AND deleted_at IS NOT NULL))
+              AND {site}
+            ORDER BY (lower(title) LIKE $1 OR lower(original_filename) LIKE $1) DESC,
+                     trashed, created_at DESC
+            LIMIT $6
+            "#,
+            site = site_filter("site_id")
+        ))
+        .bind(query.prefix_pattern())
+        .bind(query.substring_pattern())
+        .bind(user.own_content_only())
+        .bind(query.trash.live())
+        .bind(query.trash.trashed())
+        .bind(query.limit as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to search media", e))?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| AdminSearchHit {
+                kind: SearchKind::Media,
+                id: row.id.to_string(),
+                url: if row.trashed {
+                    trash_url("media", row.id)
+                } else {
+                    format!("/media?highlight={}", row.id)
+                },
+                title: row.title,
+                subtitle: row.subtitle,
+                trashed: row.trashed,
+            })
+            .collect())
+    }
+
+    /// Users by username, email or display name; administrators only
+    async fn user_hits(
+        &self,
+        user: &SearchUser,
+        query: &AdminSearchQuery,
+    ) -> Result<Vec<AdminSearchHit>> {
+        if !query.wants(SearchKind::User) || user.role() < AdminRole::Administrator {
+            return Ok(Vec::new());
+        }
+
+        let rows: Vec<EntityRow> = sqlx::query_as(
+            r#"
+            SELECT id, COALESCE(NULLIF(display_name, ''), username) AS title,
+                   email AS subtitle, deleted_at IS NOT NULL AS trashed
+            FROM users
+            WHERE (lower(username) LIKE $1 OR lower(email) LIKE $1
+                OR lower(display_name) LIKE $1
+                OR lower(username) LIKE $2 OR lower(display_name) LIKE $2)
+              AND (($3 AND deleted_at IS NULL) OR ($4 AND deleted_at IS NOT NULL))
+            ORDER BY (lower(username) LIKE $1 OR lower(display_name) LIKE $1) DESC,
+                     trashed, lower(username)
+            LIMIT $5
+            "#,
+        )
+        .bind(query.prefix_pattern())
+        .bind(query.substring_pattern())
+        .bind(query.trash.live())
+        .bind(query.trash.trashed())
+        .bind(query.limit as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to search users", e))?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| AdminSearchHit {
+                kind: SearchKind::User,
+                id: row.id.to_string(),
+                url: if row.trashed {
+                    trash_url("users", row.id)
+                } else {
+                    format!("/users?highlight={}", row.id)
+                },
+                title: row.title,
+                subtitle: row.subtitle,
+                trashed: row.trashed,
+            })
+            .collect())
+    }
+
+    /// Settings by key, where spaces in the term stand for underscores;
+    /// administrators only, and never trashed
+    async fn setting_hits(
+        &self,
+        user: &SearchUser,
+        query: &AdminSearchQuery,
+    ) -> Result<Vec<AdminSearchHit>> {
+        if !query.wants(SearchKind::Setting)
+            || !query.trash.live()
+            || user.role() < AdminRole::Administrator
+        {
+            return Ok(Vec::new());
+        }
+
+        let key = like_escape(&query.term.replace(' ', "_"));
+        let rows: Vec<SettingRow> = sqlx::query_as(
+            r#"
+            SELECT key, group_name
+            FROM settings
+            WHERE lower(key) LIKE $1 OR lower(key) LIKE $2
+            ORDER BY lower(key) LIKE $1 DESC, key
+            LIMIT $3
+            "#,
+        )
+        .bind(format!("{}%", key))
+        .bind(format!("%{}%", key))
+        .bind(query.limit as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to search settings", e))?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| AdminSearchHit {
+                kind: SearchKind::Setting,
+                title: humanize_key(&row.key),
+                subtitle: Some(format!("{} settings", humanize_key(&row.group_name))),
+                url: setting_url(&row.group_name, &row.key),
+                id: row.key,
+                trashed: false,
+            })
+            .collect())
+    }
+}
+
+/// Escape `LIKE` wildcards so the term matches literally
+fn like_escape(term: &str) -> String {
+    let mut escaped = String::with_capacity(term.len());
+    for c in term.chars() {
+        if matches!(c, '\\' | '%' | '_') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Trash view of an admin list with the entity highlighted
+fn trash_url(list: &str, id: Uuid) -> String {
+    format!("/{}?status=trash&highlight={}", list, id)
+}
+
+/// Settings screen holding a key, anchored at the field
+fn setting_url(group: &str, key: &str) -> String {
+    if SETTINGS_SCREENS.contains(&group) {
+        format!("/settings/{}#{}", group, key)
+    } else {
+        format!("/settings#{}", key)
+    }
+}
+
+/// `posts_per_page` -> `Posts per page`
+fn humanize_key(key: &str) -> String {
+    let words = key.replace(['_', '-'], " ");
+    let mut chars = words.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(role: &str) -> SearchUser {
+        SearchUser::new(Uuid::nil(), vec![role.to_string()])
+    }
+
+    #[test]
+    fn test_query_parsing() {
+        let query = AdminSearchQuery::new(
+            "  Hello   World ",
+            Some("user, post"),
+            TrashFilter::Only,
+            None,
+        )
+        .unwrap();
+        assert_eq!(query.term, "hello world");
+        assert_eq!(query.kinds, vec![SearchKind::Post, SearchKind::User]);
+        assert_eq!(query.limit, DEFAULT_HITS_PER_KIND);
+
+        let query = AdminSearchQuery::new("a", None, TrashFilter::default(), Some(500)).unwrap();
+        assert_eq!(query.kinds, SearchKind::ALL.to_vec());
+        assert_eq!(query.limit, MAX_HITS_PER_KIND);
+
+        assert!(AdminSearchQuery::new("   ", None, TrashFilter::Include, None).is_err());
+        assert!(AdminSearchQuery::new("a", Some("comment"), TrashFilter::Include, None).is_err());
+    }
+
+    #[test]
+    fn test_like_patterns() {
+        assert_eq!(like_escape(r"50%_off\"), r"50\%\_off\\");
+
+        let short = AdminSearchQuery::new("ab", None, TrashFilter::Include, None).unwrap();
+        assert_eq!(short.substring_pattern(), "ab%");
+        let long = AdminSearchQuery::new("abc", None, TrashFilter::Include, None).unwrap();
+        assert_eq!(long.prefix_pattern(), "abc%");
+        assert_eq!(long.substring_pattern(), "%abc%");
+    }
+
+    #[test]
+    fn test_roles_and_capabilities() {
+        assert_eq!(user("editor").role(), AdminRole::Editor);
+        assert_eq!(user("unknown").role(), AdminRole::Subscriber);
+        assert_eq!(user("author").own_content_only(), Some(Uuid::nil()));
+        assert_eq!(user("editor").own_content_only(), None);
+
+        assert_eq!(
+            AdminRole::for_capability(Some("read")),
+            AdminRole::Subscriber
+        );
+        assert_eq!(
+            AdminRole::for_capability(Some("moderate_comments")),
+            AdminRole::Editor
+        );
+        assert_eq!(
+            AdminRole::for_capability(Some("manage_options")),
+            AdminRole::Administrator
+        );
+        assert_eq!(AdminRole::for_capability(None), AdminRole::Administrator);
+    }
+
+    #[tokio::test]
+    async fn test_screen_hits_respect_role_and_active_plugins() {
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .connect_lazy("postgres://localhost/rustpress_test")
+            .unwrap();
+        let service = AdminSearchService::new(pool);
+        let manifest: PluginManifest = toml::from_str(
+            r#"
+            id = "forms"
+            name = "Forms"
+            version = "1.0.0"
+
+            [[admin.pages]]
+            id = "entries"
+            title = "Form entries"
+            handler = "entries"
+            capability = "edit_posts"
+            "#,
+        )
+        .unwrap();
+        service.register_plugin_pages(&[manifest]);
+
+        let query = AdminSearchQuery::new("set", None, TrashFilter::Include, Some(20)).unwrap();
+        let active = HashSet::new();
+        assert!(service
+            .screen_hits(&user("editor"), &query, &active)
+            .is_empty());
+        let hits = service.screen_hits(&user("administrator"), &query, &active);
+        assert!(hits.iter().all(|hit| hit.url.starts_with("/settings")));
+        assert_eq!(hits[0].title, "Discussion settings");
+
+        let query = AdminSearchQuery::new("entr", None, TrashFilter::Include, None).unwrap();
+        assert!(service
+            .screen_hits(&user("contributor"), &query, &active)
+            .is_empty());
+        let active: HashSet<String> = ["forms".to_string()].into();
+        let hits = service.screen_hits(&user("contributor"), &query, &active);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].url, "/plugins/forms/entries");
+    }
+
+    #[test]
+    fn test_deep_links() {
+        let id = Uuid::nil();
+        assert_eq!(
+            trash_url("posts", id),
+            format!("/posts?status=trash&highlight={}", id)
+        );
+        assert_eq!(
+            setting_url("reading", "posts_per_page"),
+            "/settings/reading#posts_per_page"
+        );
+        assert_eq!(setting_url("general", "site_title"), "/settings#site_title");
+        assert_eq!(humanize_key("posts_per_page"), "Posts per page");
+    }
+}
@@ -0,0 +1,300 @@
+//! Archive Routing
+//!
+//! Describes the archives the public site can render: term archives,
+//! including intersections such as `/category/news+featured` or
+//! `/category/news?tag=rust`, author archives and date archives
+//! (`/2024/05`, `/2024/05/14`). Custom taxonomies have term archives under
+//! their route, e.g. `/genre/jazz`. Each archive has one canonical URL.
+//! Paginated pages use `?page=N`, and page 1 is always the bare archive URL.
+//! Every archive also has a feed at `{archive}/feed` (see [`super::feeds`]).
+
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use rustpress_core::error::{Error, Result};
+
+/// Taxonomies that can also filter other archives through the query
+/// string, in canonical order
+pub const ARCHIVE_TAXONOMIES: &[&str] = &["category", "tag"];
+
+/// A term referenced by an archive URL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveTerm {
+    pub taxonomy: String,
+    pub slug: String,
+}
+
+/// A day or month date archive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateArchive {
+    pub year: i32,
+    pub month: u32,
+    pub day: Option<u32>,
+}
+
+impl DateArchive {
+    /// Validate a month or day archive
+    pub fn new(year: i32, month: u32, day: Option<u32>) -> Result<Self> {
+        if !(1..=9999).contains(&year) {
+            return Err(Error::validation(format!("Invalid archive year {}", year)));
+        }
+        NaiveDate::from_ymd_opt(year, month, day.unwrap_or(1))
+            .ok_or_else(|| Error::validation("Invalid archive date"))?;
+        Ok(Self { year, month, day })
+    }
+
+    /// Parse path segments such as `2024`, `05` and `14`
+    pub fn parse(year: &str, month: &str, day: Option<&str>) -> Result<Self> {
+        let invalid = || Error::validation("Invalid archive date");
+        let year = year.parse().map_err(|_| invalid())?;
+        let month = month.parse().map_err(|_| invalid())?;
+        let day = day.map(|d| d.parse().map_err(|_| invalid())).transpose()?;
+        Self::new(year, month, day)
+    }
+
+    /// Published-at range covered by the archive (start inclusive, end exclusive)
+    pub fn range(&self) -> (DateTime<Utc>, DateTime<Utc>) {
+        let start = self.first_day();
+        let end = match self.day {
+            Some(_) => start.succ_opt(),
+            None if self.month == 12 => NaiveDate::from_ymd_opt(self.year + 1, 1, 1),
+            None => NaiveDate::from_ymd_opt(self.year, self.month + 1, 1),
+        }
+        .unwrap_or(NaiveDate::MAX);
+
+        (midnight(start), midnight(end))
+    }
+
+    /// Human readable title, e.g. "May 2024" or "May 14, 2024"
+    pub fn title(&self) -> String {
+        let date = self.first_day();
+        match self.day {
+            Some(_) => date.format("%B %-d, %Y").to_string(),
+            None => date.format("%B %Y").to_string(),
+        }
+    }
+
+    fn first_day(&self) -> NaiveDate {
+        NaiveDate::from_ymd_opt(self.year, self.month, self.day.unwrap_or(1))
+            .unwrap_or(NaiveDate::MIN)
+    }
+}
+
+fn midnight(date: NaiveDate) -> DateTime<Utc> {
+    Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0).unwrap_or_default())
+}
+
+/// An archive request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArchiveQuery {
+    /// Posts carrying every listed term
+    Terms(Vec<ArchiveTerm>),
+    /// Posts by an author (username slug)
+    Author(String),
+    /// Posts published within a month or day
+    Date(DateArchive),
+}
+
+impl ArchiveQuery {
+    /// Single term archive
+    pub fn term(taxonomy: &str, slug: &str) -> Self {
+        Self::Terms(vec![ArchiveTerm {
+            taxonomy: taxonomy.to_string(),
+            slug: slug.to_string(),
+        }])
+    }
+
+    /// Term archive from a taxonomy route (`category`, `tag` or a custom
+    /// taxonomy's), a path segment (`news+featured`) and optional filters
+    /// on the built-in taxonomies (`("tag", "rust")`).
+    ///
+    /// Slugs are de-duplicated and sorted so that equivalent intersections
+    /// share one canonical URL.
+    pub fn terms(taxonomy: &str, path_slugs: &str, filters: &[(&str, &str)]) -> Result<Self> {
+        if !is_taxonomy_route(taxonomy) {
+            return Err(Error::validation(format!(
+                "Unknown archive taxonomy '{}'",
+                taxonomy
+            )));
+        }
+
+        let mut groups = vec![(taxonomy, split_slugs(path_slugs))];
+        if groups[0].1.is_empty() {
+            return Err(Error::validation("Archive requires at least one term"));
+        }
+
+        for name in ARCHIVE_TAXONOMIES {
+            if *name == taxonomy {
+                continue;
+            }
+            let slugs: Vec<String> = filters
+                .iter()
+                .filter(|(filter, _)| filter == name)
+                .flat_map(|(_, value)| split_slugs(value))
+                .collect();
+            if !slugs.is_empty() {
+                groups.push((name, slugs));
+            }
+        }
+
+        let mut terms = Vec::new();
+        for (taxonomy, mut slugs) in groups {
+            slugs.sort();
+            slugs.dedup();
+            terms.extend(slugs.into_iter().map(|slug| ArchiveTerm {
+                taxonomy: taxonomy.to_string(),
+                slug,
+            }));
+        }
+        Ok(Self::Terms(terms))
+    }
+
+    /// Taxonomy shown in the URL path for term archives
+    pub fn primary_taxonomy(&self) -> Option<&str> {
+        match self {
+            Self::Terms(terms) => terms.first().map(|t| t.taxonomy.as_str()),
+            _ => None,
+        }
+    }
+
+    /// Canonical path without query string
+    pub fn base_path(&self) -> String {
+        match self {
+            Self::Terms(terms) => {
+                let taxonomy = self.primary_taxonomy().unwrap_or("category");
+                format!("/{}/{}", taxonomy, join_slugs(terms, taxonomy))
+            }
+            Self::Author(slug) => format!("/author/{}", slug),
+            Self::Date(date) => match date.day {
+                Some(day) => format!("/{:04}/{:02}/{:02}", date.year, date.month, day),
+                None => format!("/{:04}/{:02}", date.year, date.month),
+            },
+        }
+    }
+
+    /// Filters on secondary taxonomies, e.g. `tag=rust+web`
+    fn query_string(&self) -> Option<String> {
+        let Self::Terms(terms) = self else {
+            return None;
+        };
+        let primary = self.primary_taxonomy()?;
+
+        let filters: Vec<String> = ARCHIVE_TAXONOMIES
+            .iter()
+            .filter(|taxonomy| **taxonomy != primary)
+            .filter(|taxonomy| terms.iter().any(|t| t.taxonomy == **taxonomy))
+            .map(|taxonomy| format!("{}={}", taxonomy, join_slugs(terms, taxonomy)))
+            .collect();
+
+        if filters.is_empty() {
+            None
+        } else {
+            Some(filters.join("&"))
+        }
+    }
+
+    /// Canonical URL path of a page of this archive
+    pub fn page_path(&self, page: i32) -> String {
+        let mut params: Vec<String> = self.query_string().into_iter().collect();
+        if page > 1 {
+            params.push(format!("page={}", page));
+        }
+
+        if params.is_empty() {
+            self.base_path()
+        } else {
+            format!("{}?{}", self.base_path(), params.join("&"))
+        }
+    }
+
+    /// RSS feed path of this archive; other formats append `/atom` or `/json`
+    pub fn feed_path(&self) -> String {
+        match self.query_string() {
+            Some(query) => format!("{}/feed?{}", self.base_path(), query),
+            None => format!("{}/feed", self.base_path()),
+        }
+    }
+}
+
+/// Whether a path segment can name a taxonomy: a lowercase letter, then
+/// lowercase letters, digits and dashes. Date archives start with a digit.
+fn is_taxonomy_route(segment: &str) -> bool {
+    segment.starts_with(|c: char| c.is_ascii_lowercase())
+        && segment
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+/// Split a slug list written as `a+b`, `a,b` or `a b` (a `+` in a query
+/// string arrives decoded as a space)
+fn split_slugs(value: &str) -> Vec<String> {
+    value
+        .split(['+', ',', ' '])
+        .map(str::trim)
+        .filter(|slug| !slug.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn join_slugs(terms: &[ArchiveTerm], taxonomy: &str) -> String {
+    terms
+        .iter()
+        .filter(|t| t.taxonomy == taxonomy)
+        .map(|t| t.slug.as_str())
+        .collect::<Vec<_>>()
+        .join("+")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_term_archive_canonicalization() {
+        let query =
+            ArchiveQuery::terms("category", "news+featured+news", &[("tag", "web rust")]).unwrap();
+        assert_eq!(query.base_path(), "/category/featured+news");
+        assert_eq!(query.page_path(1), "/category/featured+news?tag=rust+web");
+        assert_eq!(
+            query.page_path(3),
+            "/category/featured+news?tag=rust+web&page=3"
+        );
+        assert_eq!(
+            query.feed_path(),
+            "/category/featured+news/feed?tag=rust+web"
+        );
+
+        let single = ArchiveQuery::term("tag", "rust");
+        assert_eq!(single.page_path(1), "/tag/rust");
+        assert_eq!(single.page_path(2), "/tag/rust?page=2");
+
+        assert!(ArchiveQuery::terms("category", "+", &[]).is_err());
+        assert!(ArchiveQuery::terms("2024", "05", &[]).is_err());
+        assert!(ArchiveQuery::terms("Genre", "jazz", &[]).is_err());
+
+        // Custom taxonomies route their archives the same way
+        let custom = ArchiveQuery::terms("genre", "jazz+blues", &[("tag", "live")]).unwrap();
+        assert_eq!(custom.page_path(2), "/genre/blues+jazz?tag=live&page=2");
+        assert_eq!(custom.feed_path(), "/genre/blues+jazz/feed?tag=live");
+    }
+
+    #[test]
+    fn test_date_archives() {
+        let month = DateArchive::parse("2024", "05", None).unwrap();
+        let query = ArchiveQuery::Date(month);
+        assert_eq!(query.base_path(), "/2024/05");
+        assert_eq!(query.feed_path(), "/2024/05/feed");
+        assert_eq!(month.title(), "May 2024");
+
+        let (start, end) = DateArchive::new(2024, 12, None).unwrap().range();
+        assert_eq!(start.to_rfc3339(), "2024-12-01T00:00:00+00:00");
+        assert_eq!(end.to_rfc3339(), "2025-01-01T00:00:00+00:00");
+
+        let day = DateArchive::parse("2024", "2", Some("29")).unwrap();
+        assert_eq!(ArchiveQuery::Date(day).base_path(), "/2024/02/29");
+        assert_eq!(day.title(), "February 29, 2024");
+        assert_eq!(day.range().1.to_rfc3339(), "2024-03-01T00:00:00+00:00");
+
+        assert!(DateArchive::parse("2023", "02", Some("29")).is_err());
+        assert!(DateArchive::parse("2024", "13", None).is_err());
+        assert!(DateArchive::parse("feed", "05", None).is_err());
+    }
+}
@@ -0,0 +1,451 @@
+//! Author Analytics
+//!
+//! Rolls the analytics plugin's daily page rollups up to authors. Pages are
+//! mapped to published posts by path, the same way as
+//! [`content_performance`](super::content_performance), and posts to their
+//! author. Each author gets rolling 7 and 30 day views, the engagement rate
+//! and time on page across their posts over the last 30 days, and their
+//! publishing cadence.
+//!
+//! Who sees what is decided by [`AnalyticsScope`]: editors and
+//! administrators see every author, authors and contributors only their own
+//! numbers. A scoped leaderboard still ranks against everyone, so an author
+//! sees where they stand without seeing anyone else's figures.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use rustpress_core::error::{Error, Result};
+use rustpress_core::tenant::site_filter;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+
+use super::content_performance::{analytics_available, views_source};
+
+/// Authors on a leaderboard when the caller does not ask for a number
+pub const DEFAULT_LIMIT: u32 = 10;
+
+/// Most authors on a leaderboard
+pub const MAX_LIMIT: u32 = 100;
+
+/// Days publishing cadence is measured over
+const CADENCE_DAYS: i32 = 90;
+
+/// Top posts listed for a single author
+const TOP_POSTS: i64 = 5;
+
+/// Days in an author's daily view series
+const SERIES_DAYS: i32 = 30;
+
+/// Whose analytics a user may see
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnalyticsScope {
+    /// Every author
+    All,
+    /// Only the author with this id
+    Own(Uuid),
+}
+
+impl AnalyticsScope {
+    /// Scope for a user with `roles`; `None` when they may not see author
+    /// analytics at all
+    pub fn for_user(user_id: Uuid, roles: &[String]) -> Option<Self> {
+        let has = |role: &str| roles.iter().any(|r| r == role);
+        if has("administrator") || has("editor") {
+            Some(Self::All)
+        } else if has("author") || has("contributor") {
+            Some(Self::Own(user_id))
+        } else {
+            None
+        }
+    }
+
+    /// Whether an author's analytics are visible in this scope
+    pub fn allows(&self, author_id: Uuid) -> bool {
+        match self {
+            Self::All => true,
+            Self::Own(id) => *id == author_id,
+        }
+    }
+
+    fn author_filter(&self) -> Option<Uuid> {
+        match self {
+            Self::All => None,
+            Self::Own(id) => Some(*id),
+        }
+    }
+}
+
+/// Column a leaderboard is ranked by
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthorSort {
+    #[serde(rename = "views_7d")]
+    Views7d,
+    #[default]
+    #[serde(rename = "views_30d")]
+    Views30d,
+    EngagementRate,
+    PostsPerWeek,
+}
+
+impl AuthorSort {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Views7d => "views_7d",
+            Self::Views30d => "views_30d",
+            Self::EngagementRate => "engagement_rate",
+            Self::PostsPerWeek => "posts_per_week",
+        }
+    }
+
+    /// Column of the ranking query to rank by
+    fn column(&self) -> &'static str {
+        match self {
+            Self::Views7d => "s.views_7d",
+            Self::Views30d => "s.views_30d",
+            Self::EngagementRate => "s.engagement_rate",
+            Self::PostsPerWeek => "s.posts_per_week",
+        }
+    }
+}
+
+fn default_limit() -> u32 {
+    DEFAULT_LIMIT
+}
+
+/// Ranking and size of an author leaderboard
+#[derive(Debug, Clone, Deserialize)]
+pub struct LeaderboardQuery {
+    #[serde(default)]
+    pub sort: AuthorSort,
+    /// `post` or `page`; both when unset
+    pub post_type: Option<String>,
+    #[serde(default = "default_limit")]
+    pub limit: u32,
+}
+
+impl Default for LeaderboardQuery {
+    fn default() -> Self {
+        Self {
+            sort: AuthorSort::default(),
+            post_type: None,
+            limit: DEFAULT_LIMIT,
+        }
+    }
+}
+
+impl LeaderboardQuery {
+    pub fn validate(&self) -> Result<()> {
+        if let Some(post_type) = &self.post_type {
+            if post_type != "post" && post_type != "page" {
+                return Err(Error::invalid_input(
+                    "post_type",
+                    "Post type must be 'post' or 'page'",
+                ));
+            }
+        }
+        if self.limit == 0 || self.limit > MAX_LIMIT {
+            return Err(Error::invalid_input(
+                "limit",
+                format!("Limit must be between 1 and {}", MAX_LIMIT),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Rolled up analytics of one author
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct AuthorStats {
+    /// Position among all authors by the requested sort, 1 being the best
+    pub rank: i64,
+    pub author_id: Uuid,
+    pub author_name: Option<String>,
+    pub posts_published: i64,
+    pub views_7d: i64,
+    pub views_30d: i64,
+    /// Share of sessions entering on the author's posts over the last 30
+    /// days that did not bounce, 0 to 1
+    pub engagement_rate: Option<f64>,
+    /// Seconds, averaged over the last 30 days of views
+    pub avg_time_on_page: Option<f64>,
+    pub posts_last_30d: i64,
+    /// Posts per week over the last 90 days
+    pub posts_per_week: f64,
+    pub last_published_at: Option<DateTime<Utc>>,
+}
+
+/// Authors ranked by one of their stats
+#[derive(Debug, Clone, Serialize)]
+pub struct AuthorLeaderboard {
+    pub sort: AuthorSort,
+    /// Whether analytics rollups were found; without them every view count
+    /// is zero
+    pub analytics_available: bool,
+    pub items: Vec<AuthorStats>,
+}
+
+/// One of an author's most viewed posts
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct AuthorPost {
+    pub post_id: Uuid,
+    pub title: String,
+    pub path: String,
+    pub published_at: Option<DateTime<Utc>>,
+    pub views_30d: i64,
+}
+
+/// Views of an author's posts on one day
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct DailyViews {
+    pub date: NaiveDate,
+    pub views: i64,
+}
+
+/// Analytics of a single author
+#[derive(Debug, Clone, Serialize)]
+pub struct AuthorAnalytics {
+    pub analytics_available: bool,
+    #[serde(flatten)]
+    pub stats: AuthorStats,
+    pub top_posts: Vec<AuthorPost>,
+    /// Last 30 days, oldest first
+    pub daily_views: Vec<DailyViews>,
+}
+
+/// Builds author leaderboards and per-author analytics
+pub struct AuthorAnalyticsService {
+    pool: PgPool,
+}
+
+impl AuthorAnalyticsService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Authors ranked by `query.sort`, limited to those `scope` may see
+    pub async fn leaderboard(
+        &self,
+        query: &LeaderboardQuery,
+        scope: AnalyticsScope,
+    ) -> Result<AuthorLeaderboard> {
+        query.validate()?;
+
+        let analytics_available = analytics_available(&self.pool).await?;
+        let sql = Self::build_query(query.sort, analytics_available);
+        let items: Vec<AuthorStats> = sqlx::query_as(&sql)
+            .bind(query.post_type.as_deref())
+            .bind(scope.author_filter())
+            .bind(CADENCE_DAYS)
+            .bind(query.limit as i64)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to load author leaderboard", e))?;
+
+        Ok(AuthorLeaderboard {
+            sort: query.sort,
+            analytics_available,
+            items,
+        })
+    }
+
+    /// Analytics of one author, or `None` when they have published nothing
+    pub async fn author(&self, author_id: Uuid) -> Result<Option<AuthorAnalytics>> {
+        let analytics_available = analytics_available(&self.pool).await?;
+
+        let sql = Self::build_query(AuthorSort::default(), analytics_available);
+        let stats: Option<AuthorStats> = sqlx::query_as(&sql)
+            .bind(None::<&str>)
+            .bind(author_id)
+            .bind(CADENCE_DAYS)
+            .bind(1i64)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to load author analytics", e))?;
+        let Some(stats) = stats else {
+            return Ok(None);
+        };
+
+        let views = views_source(analytics_available);
+        let top_posts: Vec<AuthorPost> = sqlx::query_as(&format!(
+            r#"
+            WITH views AS ({views})
+            SELECT p.id AS post_id, p.title,
+                   '/' || p.post_type || '/' || p.slug AS path,
+                   p.published_at,
+                   COALESCE(SUM(v.pageviews) FILTER (WHERE v.data_date > CURRENT_DATE - 30), 0)::bigint AS views_30d
+            FROM posts p
+            LEFT JOIN views v ON v.path = '/' || p.post_type || '/' || p.slug
+            WHERE p.author_id = $1 AND p.status = 'published' AND p.deleted_at IS NULL
+              AND p.post_type IN ('post', 'page') AND {site}
+            GROUP BY p.id
+            ORDER BY views_30d DESC, p.published_at DESC NULLS LAST, p.id
+            LIMIT $2
+            "#,
+            site = site_filter("p.site_id")
+        ))
+        .bind(author_id)
+        .bind(TOP_POSTS)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load author top posts", e))?;
+
+        let daily_views: Vec<DailyViews> = sqlx::query_as(&format!(
+            r#"
+            WITH views AS ({views}),
+            paths AS (
+                SELECT '/' || p.post_type || '/' || p.slug AS path
+                FROM posts p
+                WHERE p.author_id = $1 AND p.status = 'published' AND p.deleted_at IS NULL
+                  AND p.post_type IN ('post', 'page') AND {site}
+            )
+            SELECT d.date::date AS date,
+                   COALESCE(SUM(v.pageviews), 0)::bigint AS views
+            FROM generate_series(CURRENT_DATE - ($2 - 1), CURRENT_DATE, INTERVAL '1 day') AS d(date)
+            LEFT JOIN views v ON v.data_date = d.date::date
+                AND v.path IN (SELECT path FROM paths)
+            GROUP BY d.date
+            ORDER BY d.date
+            "#,
+            site = site_filter("p.site_id")
+        ))
+        .bind(author_id)
+        .bind(SERIES_DAYS)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load author daily views", e))?;
+
+        Ok(Some(AuthorAnalytics {
+            analytics_available,
+            stats,
+            top_posts,
+            daily_views,
+        }))
+    }
+
+    /// Ranking query; binds are post type, author to keep, cadence days and
+    /// limit. Ranks are computed over every author before the author filter
+    /// applies.
+    fn build_query(sort: AuthorSort, analytics_available: bool) -> String {
+        format!(
+            r#"
+            WITH views AS ({views}),
+            authored AS (
+                SELECT p.id, p.author_id, p.published_at,
+                       '/' || p.post_type || '/' || p.slug AS path
+                FROM posts p
+                WHERE p.status = 'published' AND p.deleted_at IS NULL
+                  AND p.author_id IS NOT NULL AND {site}
+                  AND p.post_type IN ('post', 'page')
+                  AND ($1::text IS NULL OR p.post_type = $1)
+            ),
+            publishing AS (
+                SELECT a.author_id,
+                       COUNT(*) AS posts_published,
+                       COUNT(*) FILTER (WHERE a.published_at > NOW() - INTERVAL '30 days') AS posts_last_30d,
+                       COUNT(*) FILTER (WHERE a.published_at > NOW() - make_interval(days => $3)) AS posts_cadence,
+                       MAX(a.published_at) AS last_published_at
+                FROM authored a
+                GROUP BY a.author_id
+            ),
+            traffic AS (
+                SELECT a.author_id,
+                       COALESCE(SUM(v.pageviews) FILTER (WHERE v.data_date > CURRENT_DATE - 7), 0) AS views_7d,
+                       COALESCE(SUM(v.pageviews) FILTER (WHERE v.data_date > CURRENT_DATE - 30), 0) AS views_30d,
+                       SUM(v.sessions) FILTER (WHERE v.data_date > CURRENT_DATE - 30) AS sessions,
+                       SUM(v.bounces) FILTER (WHERE v.data_date > CURRENT_DATE - 30) AS bounces,
+                       SUM(v.time_on_page) FILTER (WHERE v.data_date > CURRENT_DATE - 30) AS time_on_page
+                FROM authored a
+                LEFT JOIN views v ON v.path = a.path
+                GROUP BY a.author_id
+            ),
+            stats AS (
+                SELECT p.author_id, p.posts_published, p.posts_last_30d, p.last_published_at,
+                       t.views_7d, t.views_30d,
+                       CASE WHEN t.sessions > 0 THEN 1 - t.bounces / t.sessions END AS engagement_rate,
+                       CASE WHEN t.views_30d > 0 THEN t.time_on_page / t.views_30d END AS avg_time_on_page,
+                       p.posts_cadence * 7.0 / $3 AS posts_per_week
+                FROM publishing p
+                JOIN traffic t ON t.author_id = p.author_id
+            ),
+            ranked AS (
+                SELECT s.*, RANK() OVER (ORDER BY {column} DESC NULLS LAST) AS rank
+                FROM stats s
+            )
+            SELECT r.rank, r.author_id,
+                   COALESCE(u.display_name, u.username) AS author_name,
+                   r.posts_published,
+                   r.views_7d::bigint AS views_7d,
+                   r.views_30d::bigint AS views_30d,
+                   r.engagement_rate::float8 AS engagement_rate,
+                   r.avg_time_on_page::float8 AS avg_time_on_page,
+                   r.posts_last_30d,
+                   r.posts_per_week::float8 AS posts_per_week,
+                   r.last_published_at
+            FROM ranked r
+            LEFT JOIN users u ON u.id = r.author_id
+            WHERE $2::uuid IS NULL OR r.author_id = $2
+            ORDER BY r.rank, r.author_id
+            LIMIT $4
+            "#,
+            views = views_source(analytics_available),
+            site = site_filter("p.site_id"),
+            column = sort.column(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scope_for_user() {
+        let id = Uuid::now_v7();
+        let roles = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(
+            AnalyticsScope::for_user(id, &roles(&["editor"])),
+            Some(AnalyticsScope::All)
+        );
+        assert_eq!(
+            AnalyticsScope::for_user(id, &roles(&["author"])),
+            Some(AnalyticsScope::Own(id))
+        );
+        assert_eq!(AnalyticsScope::for_user(id, &roles(&["subscriber"])), None);
+
+        let own = AnalyticsScope::Own(id);
+        assert!(own.allows(id));
+        assert!(!own.allows(Uuid::now_v7()));
+        assert!(AnalyticsScope::All.allows(id));
+    }
+
+    #[test]
+    fn test_leaderboard_query_validation() {
+        assert!(LeaderboardQuery::default().validate().is_ok());
+
+        let too_many = LeaderboardQuery {
+            limit: MAX_LIMIT + 1,
+            ..Default::default()
+        };
+        assert!(too_many.validate().is_err());
+
+        let attachments = LeaderboardQuery {
+            post_type: Some("attachment".to_string()),
+            ..Default::default()
+        };
+        assert!(attachments.validate().is_err());
+    }
+
+    #[test]
+    fn test_sort_names() {
+        let sort: AuthorSort = serde_json::from_str("\"views_7d\"").unwrap();
+        assert_eq!(sort, AuthorSort::Views7d);
+        assert_eq!(AuthorSort::default().as_str(), "views_30d");
+
+        let sql = AuthorAnalyticsService::build_query(AuthorSort::EngagementRate, false);
+        assert!(sql.contains("ORDER BY s.engagement_rate DESC NULLS LAST"));
+        assert!(!sql.contains("rustanalytics_daily_data"));
+    }
+}
+
@@ -0,0 +1,480 @@
+//! Avatars
+//!
+//! Serves the image behind `/avatar/{id}` for every avatar source:
+//!
+//! - uploads, cropped to a square by the media image pipeline (which also
+//!   strips EXIF data) and kept in storage under `avatars/{user_id}/`
+//! - Gravatar, fetched server-side so browsers never contact gravatar.com
+//!   or see the email hash
+//! - generated initials, used when there is nothing else to show
+//!
+//! Media library avatars redirect to the upload itself. Templates and APIs
+//! should build URLs with [`avatar_url`] rather than formatting them.
+
+use bytes::Bytes;
+use parking_lot::Mutex;
+use rustpress_core::error::{Error, Result};
+use rustpress_core::http::HttpClient;
+use rustpress_media::ImageOptimizer;
+use rustpress_storage::Storage;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+use super::render_service::AuthorData;
+use super::user_profile::{AvatarSource, AvatarSubject, GravatarSettings, ProfileView};
+use super::ProfileService;
+
+/// Sizes the avatar endpoint serves; requests round up to the next one
+pub const AVATAR_SIZES: [u32; 7] = [32, 48, 64, 96, 128, 256, 512];
+
+/// Size used when a caller does not ask for one
+pub const DEFAULT_AVATAR_SIZE: u32 = 96;
+
+/// Largest accepted avatar upload
+pub const MAX_AVATAR_UPLOAD: usize = 5 * 1024 * 1024;
+
+/// How long a resolved avatar image is reused
+const AVATAR_CACHE_TTL: Duration = Duration::from_secs(3600);
+
+/// Resolved images kept in memory
+const AVATAR_CACHE_CAPACITY: usize = 1024;
+
+/// Background colours for generated initials
+const INITIALS_PALETTE: [&str; 8] = [
+    "#1e88e5", "#43a047", "#e53935", "#8e24aa", "#fb8c00", "#00897b", "#3949ab", "#6d4c41",
+];
+
+/// Avatar image bytes
+#[derive(Debug, Clone)]
+pub struct AvatarImage {
+    pub content_type: String,
+    pub bytes: Bytes,
+}
+
+/// What `/avatar/{id}` should answer with
+#[derive(Debug, Clone)]
+pub enum ResolvedAvatar {
+    Image(AvatarImage),
+    /// Media library avatars are served from their upload URL
+    Redirect(String),
+}
+
+/// Anything that has an avatar
+pub trait HasAvatar {
+    fn avatar_user_id(&self) -> Option<Uuid>;
+
+    /// The stored `users.avatar_url` value
+    fn stored_avatar_url(&self) -> Option<&str>;
+}
+
+impl HasAvatar for ProfileView {
+    fn avatar_user_id(&self) -> Option<Uuid> {
+        Some(self.user_id)
+    }
+
+    fn stored_avatar_url(&self) -> Option<&str> {
+        self.avatar_url.as_deref()
+    }
+}
+
+impl HasAvatar for AuthorData {
+    fn avatar_user_id(&self) -> Option<Uuid> {
+        Uuid::parse_str(&self.id).ok()
+    }
+
+    fn stored_avatar_url(&self) -> Option<&str> {
+        self.avatar_url.as_deref()
+    }
+}
+
+/// Template values (`post.author`, `user`, ...) with `id` and `avatar_url`
+impl HasAvatar for serde_json::Value {
+    fn avatar_user_id(&self) -> Option<Uuid> {
+        match self {
+            serde_json::Value::String(id) => Uuid::parse_str(id).ok(),
+            _ => self
+                .get("id")
+                .and_then(|v| v.as_str())
+                .and_then(|id| Uuid::parse_str(id).ok()),
+        }
+    }
+
+    fn stored_avatar_url(&self) -> Option<&str> {
+        self.get("avatar_url").and_then(|v| v.as_str())
+    }
+}
+
+/// Avatar URL of a user at the given size
+pub fn avatar_url(user: &impl HasAvatar, size: u32) -> Option<String> {
+    match user.avatar_user_id() {
+        Some(id) => Some(avatar_url_for(id, user.stored_avatar_url(), size)),
+        None => user.stored_avatar_url().map(str::to_string),
+    }
+}
+
+/// Avatar URL from a user ID and its stored `avatar_url`.
+///
+/// Media library and external URLs are returned as stored; everything
+/// else goes through `/avatar/{id}`, keeping the stored cache-busting
+/// version.
+pub fn avatar_url_for(user_id: Uuid, stored: Option<&str>, size: u32) -> String {
+    if let Some(stored) = stored.filter(|s| !s.is_empty() && !s.starts_with("/avatar/")) {
+        return stored.to_string();
+    }
+
+    let version = stored
+        .and_then(|s| s.split_once('?'))
+        .and_then(|(_, query)| query.split('&').find_map(|p| p.strip_prefix("v=")));
+    match version {
+        Some(v) => format!("/avatar/{}?s={}&v={}", user_id, avatar_size(size), v),
+        None => format!("/avatar/{}?s={}", user_id, avatar_size(size)),
+    }
+}
+
+/// A fresh cache-busting version for a stored avatar URL
+pub fn avatar_version() -> String {
+    format!("{:x}", chrono::Utc::now().timestamp_millis())
+}
+
+/// Tera function: `avatar_url(user=post.author, size=48)`
+pub fn tera_avatar_url(args: &HashMap<String, tera::Value>) -> tera::Result<tera::Value> {
+    let user = args
+        .get("user")
+        .ok_or_else(|| tera::Error::msg("avatar_url: missing 'user' argument"))?;
+    let size = args
+        .get("size")
+        .and_then(|v| v.as_u64())
+        .map_or(DEFAULT_AVATAR_SIZE, |s| s.min(u32::MAX as u64) as u32);
+
+    Ok(tera::Value::String(
+        avatar_url(user, size).unwrap_or_default(),
+    ))
+}
+
+/// Round a requested size up to one the endpoint serves
+pub fn avatar_size(requested: u32) -> u32 {
+    AVATAR_SIZES
+        .iter()
+        .copied()
+        .find(|size| *size >= requested)
+        .unwrap_or(AVATAR_SIZES[AVATAR_SIZES.len() - 1])
+}
+
+/// Gravatar SHA-256 hash of an email address
+fn gravatar_hash(email: &str) -> String {
+    let digest = Sha256::digest(email.trim().to_lowercase().as_bytes());
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Up to two initials from a display name
+pub fn initials(name: &str) -> String {
+    let initials: String = name
+        .split_whitespace()
+        .filter_map(|word| word.chars().find(|c| c.is_alphanumeric()))
+        .take(2)
+        .flat_map(char::to_uppercase)
+        .collect();
+    if initials.is_empty() {
+        "?".to_string()
+    } else {
+        initials
+    }
+}
+
+/// Square SVG with the user's initials on a colour derived from their ID
+pub fn initials_svg(name: &str, user_id: Uuid, size: u32) -> String {
+    let colour = INITIALS_PALETTE[user_id.as_bytes()[15] as usize % INITIALS_PALETTE.len()];
+    format!(
+        concat!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{size}" height="{size}" viewBox="0 0 100 100">"#,
+            r#"<rect width="100" height="100" fill="{colour}"/>"#,
+            r##"<text x="50" y="50" dy=".35em" text-anchor="middle" fill="#fff" "##,
+            r#"font-family="system-ui, sans-serif" font-size="40">{initials}</text></svg>"#
+        ),
+        size = size,
+        colour = colour,
+        initials = tera::escape_html(&initials(name)),
+    )
+}
+
+/// Uploads, Gravatar proxy and initials fallback behind `/avatar/{id}`
+pub struct AvatarService {
+    storage: Arc<Storage>,
+    profiles: Arc<ProfileService>,
+    http: HttpClient,
+    cache: Mutex<HashMap<(Uuid, u32), (Instant, AvatarImage)>>,
+}
+
+impl AvatarService {
+    pub fn new(storage: Arc<Storage>, profiles: Arc<ProfileService>, http: HttpClient) -> Self {
+        Self {
+            storage,
+            profiles,
+            http,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Image for a user's avatar; `None` when the user does not exist
+    pub async fn resolve(&self, user_id: Uuid, size: u32) -> Result<Option<ResolvedAvatar>> {
+        let size = avatar_size(size);
+        if let Some((fetched_at, image)) = self.cache.lock().get(&(user_id, size)) {
+            if fetched_at.elapsed() < AVATAR_CACHE_TTL {
+                return Ok(Some(ResolvedAvatar::Image(image.clone())));
+            }
+        }
+
+        let Some(subject) = self.profiles.avatar_subject(user_id).await? else {
+            return Ok(None);
+        };
+
+        let image = match subject.source {
+            AvatarSource::Media { media_id } => {
+                let url = self.profiles.media_avatar_url(media_id).await?;
+                return Ok(Some(ResolvedAvatar::Redirect(url)));
+            }
+            AvatarSource::Upload { ref path } => match self.uploaded(path, size).await {
+                Ok(image) => Some(image),
+                Err(e) => {
+                    tracing::warn!(user_id = %user_id, "Failed to load uploaded avatar: {}", e);
+                    None
+                }
+            },
+            AvatarSource::Gravatar => {
+                let config = self.profiles.config().await;
+                match self.gravatar(&subject, size, &config.gravatar).await {
+                    Ok(image) => image,
+                    Err(e) => {
+                        tracing::warn!(user_id = %user_id, "Gravatar proxy failed: {}", e);
+                        None
+                    }
+                }
+            }
+            AvatarSource::None => None,
+        };
+
+        let image = image.unwrap_or_else(|| AvatarImage {
+            content_type: "image/svg+xml".to_string(),
+            bytes: Bytes::from(initials_svg(&subject.display_name, user_id, size)),
+        });
+
+        let mut cache = self.cache.lock();
+        if cache.len() >= AVATAR_CACHE_CAPACITY {
+            cache.retain(|_, (fetched_at, _)| fetched_at.elapsed() < AVATAR_CACHE_TTL);
+            if cache.len() >= AVATAR_CACHE_CAPACITY {
+                cache.clear();
+            }
+        }
+        cache.insert((user_id, size), (Instant::now(), image.clone()));
+        Ok(Some(ResolvedAvatar::Image(image)))
+    }
+
+    /// Store an uploaded image as the user's avatar
+    pub async fn upload(&self, user_id: Uuid, data: Bytes) -> Result<ProfileView> {
+        if data.len() > MAX_AVATAR_UPLOAD {
+            return Err(Error::invalid_input(
+                "file",
+                format!("Avatar must be at most {} bytes", MAX_AVATAR_UPLOAD),
+            ));
+        }
+
+        let master_size = AVATAR_SIZES[AVATAR_SIZES.len() - 1];
+        let master = tokio::task::spawn_blocking(move || {
+            ImageOptimizer::detect_format(&data)?;
+            ImageOptimizer::default_config().generate_thumbnail_exact(
+                &data,
+                master_size,
+                master_size,
+            )
+        })
+        .await
+        .map_err(|e| Error::internal(format!("Avatar processing failed: {}", e)))?
+        .map_err(|e| Error::invalid_input("file", format!("Unsupported image: {}", e)))?;
+
+        let stored = self
+            .storage
+            .upload_to(
+                Bytes::from(master),
+                "avatar.jpg",
+                "image/jpeg",
+                &format!("avatars/{}", user_id),
+            )
+            .await?;
+
+        let (previous, view) = match self
+            .profiles
+            .set_avatar(
+                user_id,
+                AvatarSource::Upload {
+                    path: stored.path.clone(),
+                },
+            )
+            .await
+        {
+            Ok(result) => result,
+            Err(e) => {
+                let _ = self.storage.delete(&stored.path).await;
+                return Err(e);
+            }
+        };
+        self.discard(user_id, previous).await;
+        Ok(view)
+    }
+
+    /// Remove an uploaded avatar, falling back to initials
+    pub async fn remove(&self, user_id: Uuid) -> Result<ProfileView> {
+        let (previous, view) = self
+            .profiles
+            .set_avatar(user_id, AvatarSource::None)
+            .await?;
+        self.discard(user_id, previous).await;
+        Ok(view)
+    }
+
+    /// Forget cached images of one user
+    pub fn invalidate(&self, user_id: Uuid) {
+        self.cache
+            .lock()
+            .retain(|(cached_user, _), _| *cached_user != user_id);
+    }
+
+    /// Forget all cached images, e.g. after Gravatar settings change
+    pub fn clear(&self) {
+        self.cache.lock().clear();
+    }
+
+    async fn discard(&self, user_id: Uuid, previous: AvatarSource) {
+        self.invalidate(user_id);
+        if let AvatarSource::Upload { path } = previous {
+            if let Err(e) = self.storage.delete(&path).await {
+                tracing::warn!(user_id = %user_id, "Failed to delete old avatar: {}", e);
+            }
+        }
+    }
+
+    async fn uploaded(&self, path: &str, size: u32) -> Result<AvatarImage> {
+        let master = self.storage.get(path).await?;
+        let bytes = tokio::task::spawn_blocking(move || {
+            ImageOptimizer::default_config().generate_thumbnail_exact(&master, size, size)
+        })
+        .await
+        .map_err(|e| Error::internal(format!("Avatar processing failed: {}", e)))?
+        .map_err(|e| Error::internal(format!("Avatar processing failed: {}", e)))?;
+
+        Ok(AvatarImage {
+            content_type: "image/jpeg".to_string(),
+            bytes: Bytes::from(bytes),
+        })
+    }
+
+    /// Fetch a Gravatar; `None` when the proxy is disabled or the address
+    /// has no Gravatar and the initials fallback is configured
+    async fn gravatar(
+        &self,
+        subject: &AvatarSubject,
+        size: u32,
+        settings: &GravatarSettings,
+    ) -> Result<Option<AvatarImage>> {
+        if !settings.enabled {
+            return Ok(None);
+        }
+
+        let default_image = match settings.default_image.as_str() {
+            "initials" => "404",
+            other => other,
+        };
+        let url = format!(
+            "https://gravatar.com/avatar/{}?s={}&d={}&r={}",
+            gravatar_hash(&subject.email),
+            size,
+            urlencoding::encode(default_image),
+            urlencoding::encode(&settings.rating),
+        );
+        let response = self
+            .http
+            .send(self.http.get(&url).timeout(Duration::from_secs(5)))
+            .await
+            .map_err(|e| Error::internal(format!("Gravatar request failed: {}", e)))?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let response = response
+            .error_for_status()
+            .map_err(|e| Error::internal(format!("Gravatar request failed: {}", e)))?;
+
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .filter(|v| v.starts_with("image/"))
+            .unwrap_or("image/jpeg")
+            .to_string();
+        let bytes = response
+            .bytes()
+            .await
+            .map_err(|e| Error::internal(format!("Gravatar request failed: {}", e)))?;
+
+        Ok(Some(AvatarImage {
+            content_type,
+            bytes,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_avatar_urls() {
+        let id = Uuid::nil();
+        assert_eq!(avatar_url_for(id, None, 50), format!("/avatar/{}?s=64", id));
+        assert_eq!(
+            avatar_url_for(id, Some(&format!("/avatar/{}?v=18c", id)), 4096),
+            format!("/avatar/{}?s=512&v=18c", id)
+        );
+        assert_eq!(
+            avatar_url_for(id, Some("/uploads/2024/01/01/me.png"), 96),
+            "/uploads/2024/01/01/me.png"
+        );
+
+        let author = json!({"id": id.to_string(), "avatar_url": null});
+        assert_eq!(
+            avatar_url(&author, 32),
+            Some(format!("/avatar/{}?s=32", id))
+        );
+        assert_eq!(avatar_url(&json!({"name": "x"}), 32), None);
+
+        let args = HashMap::from([
+            ("user".to_string(), author),
+            ("size".to_string(), json!(48)),
+        ]);
+        assert_eq!(
+            tera_avatar_url(&args).unwrap(),
+            json!(format!("/avatar/{}?s=48", id))
+        );
+    }
+
+    #[test]
+    fn test_initials_and_hash() {
+        assert_eq!(initials("Ada Lovelace"), "AL");
+        assert_eq!(initials("  jane  q  public "), "JQ");
+        assert_eq!(initials("<>"), "?");
+
+        let svg = initials_svg("<script> Doe", Uuid::nil(), 64);
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains(">SD</text>"));
+        assert!(!svg.contains("<script>"));
+
+        assert_eq!(
+            gravatar_hash("JDoe@Example.com "),
+            gravatar_hash("jdoe@example.com")
+        );
+        assert_eq!(avatar_size(50), 64);
+        assert_eq!(avatar_size(4096), 512);
+    }
+}
@@ -0,0 +1,573 @@
+//! Cache-Control Policy Engine
+//!
+//! Maps public routes and content types to `Cache-Control` and
+//! `Surrogate-Control` headers so a CDN can hold rendered HTML for longer
+//! than browsers do (`s-maxage`) and keep serving it while it revalidates
+//! (`stale-while-revalidate`). Rendered pages also carry surrogate keys
+//! (`post:{id}`, `post_type:{type}`, `term:{taxonomy}:{id}`, `user:{id}`)
+//! named like the cache tags used for invalidation, so purging a tag at
+//! the CDN drops exactly the pages that show that content. Single posts
+//! and pages can override the policy through `cache_policy` meta.
+
+use regex::Regex;
+use rustpress_core::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::PgPool;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use super::json_setting::JsonSetting;
+use super::render_service::PostData;
+
+/// Settings key holding the cache policy configuration
+pub const CACHE_POLICY_SETTINGS_KEY: &str = "cache_policy";
+
+/// Stored cache policy
+const CACHE_POLICY_SETTING: JsonSetting<CachePolicyConfig> =
+    JsonSetting::new(CACHE_POLICY_SETTINGS_KEY, "cache", "cache policy");
+
+/// Post meta key holding a per-post [`CacheOverride`]
+pub const CACHE_POLICY_META_KEY: &str = "cache_policy";
+
+/// Who may store a response
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheVisibility {
+    /// Browsers and shared caches
+    #[default]
+    Public,
+    /// Browsers only
+    Private,
+    /// Nobody
+    NoStore,
+}
+
+/// Caching directives for a class of responses
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CachePolicy {
+    pub visibility: CacheVisibility,
+    /// Browser lifetime in seconds
+    pub max_age: u32,
+    /// Shared cache (CDN) lifetime in seconds
+    pub s_maxage: Option<u32>,
+    pub stale_while_revalidate: Option<u32>,
+    pub stale_if_error: Option<u32>,
+}
+
+impl Default for CachePolicy {
+    fn default() -> Self {
+        Self {
+            visibility: CacheVisibility::Public,
+            max_age: 60,
+            s_maxage: Some(600),
+            stale_while_revalidate: Some(60),
+            stale_if_error: Some(86400),
+        }
+    }
+}
+
+impl CachePolicy {
+    fn public(max_age: u32, s_maxage: u32) -> Self {
+        Self {
+            max_age,
+            s_maxage: Some(s_maxage),
+            ..Default::default()
+        }
+    }
+
+    /// Policy for personalised or preview responses
+    pub fn no_store() -> Self {
+        Self {
+            visibility: CacheVisibility::NoStore,
+            max_age: 0,
+            s_maxage: None,
+            stale_while_revalidate: None,
+            stale_if_error: None,
+        }
+    }
+
+    /// `Cache-Control` header value
+    pub fn cache_control(&self) -> String {
+        match self.visibility {
+            CacheVisibility::NoStore => "private, no-store".to_string(),
+            CacheVisibility::Private => format!("private, max-age={}", self.max_age),
+            CacheVisibility::Public => {
+                let mut directives =
+                    vec!["public".to_string(), format!("max-age={}", self.max_age)];
+                if let Some(s_maxage) = self.s_maxage {
+                    directives.push(format!("s-maxage={}", s_maxage));
+                }
+                if let Some(swr) = self.stale_while_revalidate {
+                    directives.push(format!("stale-while-revalidate={}", swr));
+                }
+                if let Some(sie) = self.stale_if_error {
+                    directives.push(format!("stale-if-error={}", sie));
+                }
+                directives.join(", ")
+            }
+        }
+    }
+
+    /// `Surrogate-Control` header value for CDNs that honour it
+    pub fn surrogate_control(&self) -> Option<String> {
+        if self.visibility != CacheVisibility::Public {
+            return None;
+        }
+        let mut directives = vec![format!("max-age={}", self.s_maxage?)];
+        if let Some(swr) = self.stale_while_revalidate {
+            directives.push(format!("stale-while-revalidate={}", swr));
+        }
+        if let Some(sie) = self.stale_if_error {
+            directives.push(format!("stale-if-error={}", sie));
+        }
+        Some(directives.join(", "))
+    }
+
+    fn with_override(mut self, patch: &CacheOverride) -> Self {
+        if let Some(visibility) = patch.visibility {
+            self.visibility = visibility;
+        }
+        if let Some(max_age) = patch.max_age {
+            self.max_age = max_age;
+        }
+        if patch.s_maxage.is_some() {
+            self.s_maxage = patch.s_maxage;
+        }
+        if patch.stale_while_revalidate.is_some() {
+            self.stale_while_revalidate = patch.stale_while_revalidate;
+        }
+        self
+    }
+}
+
+/// Per-post adjustments to the matched policy
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CacheOverride {
+    pub visibility: Option<CacheVisibility>,
+    pub max_age: Option<u32>,
+    pub s_maxage: Option<u32>,
+    pub stale_while_revalidate: Option<u32>,
+}
+
+impl CacheOverride {
+    /// Read the override from post meta (a JSON object or JSON text)
+    pub fn from_meta(meta: &HashMap<String, Value>) -> Option<Self> {
+        match meta.get(CACHE_POLICY_META_KEY)? {
+            Value::String(text) => serde_json::from_str(text).ok(),
+            value => serde_json::from_value(value.clone()).ok(),
+        }
+    }
+}
+
+/// Route and content type mapped to a policy
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachePolicyRule {
+    /// Path glob: `*` matches within a segment, `**` across segments
+    pub pattern: String,
+    /// Content type prefix the response must have (any when unset)
+    #[serde(default)]
+    pub content_type: Option<String>,
+    pub policy: CachePolicy,
+}
+
+impl CachePolicyRule {
+    fn new(pattern: &str, content_type: Option<&str>, policy: CachePolicy) -> Self {
+        Self {
+            pattern: pattern.to_string(),
+            content_type: content_type.map(str::to_string),
+            policy,
+        }
+    }
+}
+
+/// Cache policy configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CachePolicyConfig {
+    pub enabled: bool,
+    /// Checked in order; the first match wins
+    pub rules: Vec<CachePolicyRule>,
+    /// Policy for HTML responses no rule matched
+    pub default_policy: CachePolicy,
+    /// Emit `Surrogate-Key` and `Cache-Tag` headers
+    pub surrogate_keys: bool,
+}
+
+impl Default for CachePolicyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            rules: vec![
+                CachePolicyRule::new("/themes/**", None, CachePolicy::public(31536000, 31536000)),
+                CachePolicyRule::new("/robots.txt", None, CachePolicy::public(3600, 3600)),
+                CachePolicyRule::new("/sitemap.xml", None, CachePolicy::public(3600, 3600)),
+                CachePolicyRule::new("**/feed", None, CachePolicy::public(300, 900)),
+                CachePolicyRule::new("**/feed/*", None, CachePolicy::public(300, 900)),
+                CachePolicyRule::new("/search", None, CachePolicy::public(0, 60)),
+            ],
+            default_policy: CachePolicy::default(),
+            surrogate_keys: true,
+        }
+    }
+}
+
+impl CachePolicyConfig {
+    /// Load the configuration from settings, falling back to defaults
+    pub async fn load(pool: &PgPool) -> Result<Self> {
+        CACHE_POLICY_SETTING.load_or_default(pool).await
+    }
+
+    /// Persist the configuration to settings
+    pub async fn save(&self, pool: &PgPool) -> Result<()> {
+        CACHE_POLICY_SETTING.save(pool, self).await
+    }
+}
+
+/// Headers to set on a response
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CacheHeaders {
+    pub cache_control: String,
+    pub surrogate_control: Option<String>,
+    pub surrogate_keys: Vec<String>,
+}
+
+/// Hints a handler attaches to its response for the policy middleware
+#[derive(Debug, Clone, Default)]
+pub struct CacheHints {
+    pub surrogate_keys: Vec<String>,
+    pub cache_override: Option<CacheOverride>,
+}
+
+/// Request facts that decide whether a response may be shared
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CacheRequest<'a> {
+    pub path: &'a str,
+    pub content_type: &'a str,
+    pub status: u16,
+    /// Authenticated, preview or otherwise user-specific
+    pub personalized: bool,
+}
+
+/// Compiled cache policy rules
+pub struct CachePolicyEngine {
+    config: CachePolicyConfig,
+    patterns: Vec<Regex>,
+}
+
+impl CachePolicyEngine {
+    pub fn new(config: CachePolicyConfig) -> Result<Self> {
+        let patterns = config
+            .rules
+            .iter()
+            .map(|rule| compile_glob(&rule.pattern))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { config, patterns })
+    }
+
+    pub fn config(&self) -> &CachePolicyConfig {
+        &self.config
+    }
+
+    /// Headers for a response, or `None` to leave the handler's headers alone
+    pub fn resolve(
+        &self,
+        request: &CacheRequest<'_>,
+        hints: Option<&CacheHints>,
+    ) -> Option<CacheHeaders> {
+        if !self.config.enabled {
+            return None;
+        }
+        if request.personalized {
+            return Some(CacheHeaders {
+                cache_control: CachePolicy::no_store().cache_control(),
+                surrogate_control: None,
+                surrogate_keys: Vec::new(),
+            });
+        }
+        // Errors and redirects other than permanent ones keep their own headers
+        if !matches!(request.status, 200 | 301 | 404) {
+            return None;
+        }
+
+        let matched = self
+            .config
+            .rules
+            .iter()
+            .zip(&self.patterns)
+            .find(|(rule, pattern)| {
+                pattern.is_match(request.path)
+                    && rule
+                        .content_type
+                        .iter()
+                        .all(|prefix| request.content_type.starts_with(prefix.as_str()))
+            })
+            .map(|(rule, _)| rule.policy.clone());
+        let mut policy = match matched {
+            Some(policy) => policy,
+            None if request.content_type.starts_with("text/html") => {
+                self.config.default_policy.clone()
+            }
+            None => return None,
+        };
+
+        if let Some(patch) = hints.and_then(|h| h.cache_override.as_ref()) {
+            policy = policy.with_override(patch);
+        }
+        // Missing pages may be created at any moment; keep them short-lived
+        if request.status == 404 {
+            policy.max_age = policy.max_age.min(60);
+            policy.s_maxage = policy.s_maxage.map(|s| s.min(60));
+        }
+
+        let surrogate_keys = match hints {
+            Some(hints) if self.config.surrogate_keys => hints.surrogate_keys.clone(),
+            _ => Vec::new(),
+        };
+
+        Some(CacheHeaders {
+            cache_control: policy.cache_control(),
+            surrogate_control: policy.surrogate_control(),
+            surrogate_keys,
+        })
+    }
+}
+
+/// Convert a path glob to an anchored regex
+fn compile_glob(pattern: &str) -> Result<Regex> {
+    if !pattern.starts_with('/') && !pattern.starts_with('*') {
+        return Err(Error::invalid_input(
+            "pattern",
+            format!("Pattern '{}' must start with '/' or '*'", pattern),
+        ));
+    }
+    let regex = regex::escape(pattern)
+        .replace(r"\*\*", ".*")
+        .replace(r"\*", "[^/]*");
+    Regex::new(&format!("^{}/?$", regex))
+        .map_err(|e| Error::invalid_input("pattern", format!("Invalid pattern: {}", e)))
+}
+
+/// Surrogate keys named like the cache invalidation tags
+#[derive(Debug, Clone, Default)]
+pub struct SurrogateKeys {
+    keys: BTreeSet<String>,
+}
+
+impl SurrogateKeys {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn post(mut self, post_id: &str) -> Self {
+        self.keys.insert(format!("post:{}", post_id));
+        self
+    }
+
+    pub fn post_type(mut self, post_type: &str) -> Self {
+        self.keys.insert(format!("post_type:{}", post_type));
+        self
+    }
+
+    pub fn term(mut self, taxonomy: &str, term_id: &str) -> Self {
+        self.keys.insert(format!("term:{}:{}", taxonomy, term_id));
+        self
+    }
+
+    pub fn user(mut self, user_id: &str) -> Self {
+        self.keys.insert(format!("user:{}", user_id));
+        self
+    }
+
+    /// Keys for a single post or page and everything shown with it
+    pub fn for_post(post: &PostData) -> Self {
+        let keys = Self::new()
+            .post(&post.id)
+            .post_type(&post.post_type)
+            .user(&post.author.id);
+        post.categories
+            .iter()
+            .chain(&post.tags)
+            .fold(keys, |keys, term| keys.term(&term.taxonomy, &term.id))
+    }
+
+    pub fn build(self) -> Vec<String> {
+        self.keys.into_iter().collect()
+    }
+}
+
+/// Loads and caches the compiled cache policy
+pub struct CachePolicyService {
+    pool: PgPool,
+    engine: RwLock<Option<Arc<CachePolicyEngine>>>,
+}
+
+impl CachePolicyService {
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            engine: RwLock::new(None),
+        }
+    }
+
+    /// Compiled policy, loaded from settings on first use
+    pub async fn engine(&self) -> Arc<CachePolicyEngine> {
+        if let Some(engine) = self.engine.read().await.clone() {
+            return engine;
+        }
+
+        let engine = match CachePolicyConfig::load(&self.pool)
+            .await
+            .and_then(CachePolicyEngine::new)
+        {
+            Ok(engine) => engine,
+            Err(e) => {
+                tracing::warn!("Failed to load cache policy, using defaults: {}", e);
+                CachePolicyEngine::new(CachePolicyConfig::default())
+                    .expect("default cache policy compiles")
+            }
+        };
+        let engine = Arc::new(engine);
+        *self.engine.write().await = Some(engine.clone());
+        engine
+    }
+
+    /// Replace the cached policy after it was saved
+    pub async fn set_engine(&self, engine: CachePolicyEngine) {
+        *self.engine.write().await = Some(Arc::new(engine));
+    }
+
+    /// Drop the cached policy; the next use reloads it
+    pub async fn invalidate(&self) {
+        *self.engine.write().await = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn html(path: &str) -> CacheRequest<'_> {
+        CacheRequest {
+            path,
+            content_type: "text/html; charset=utf-8",
+            status: 200,
+            personalized: false,
+        }
+    }
+
+    #[test]
+    fn test_rules_and_default_policy() {
+        let engine = CachePolicyEngine::new(CachePolicyConfig::default()).unwrap();
+
+        let page = engine.resolve(&html("/post/hello"), None).unwrap();
+        assert_eq!(
+            page.cache_control,
+            "public, max-age=60, s-maxage=600, stale-while-revalidate=60, stale-if-error=86400"
+        );
+        assert_eq!(
+            page.surrogate_control.as_deref(),
+            Some("max-age=600, stale-while-revalidate=60, stale-if-error=86400")
+        );
+
+        let feed = engine
+            .resolve(
+                &CacheRequest {
+                    content_type: "application/rss+xml",
+                    ..html("/category/news/feed")
+                },
+                None,
+            )
+            .unwrap();
+        assert!(feed.cache_control.contains("s-maxage=900"));
+
+        let asset = CacheRequest {
+            content_type: "text/css",
+            ..html("/themes/flavor/css/site.css")
+        };
+        assert!(engine
+            .resolve(&asset, None)
+            .unwrap()
+            .cache_control
+            .contains("max-age=31536000"));
+
+        let json = CacheRequest {
+            content_type: "application/json",
+            ..html("/something")
+        };
+        assert!(engine.resolve(&json, None).is_none());
+
+        let private = CacheRequest {
+            personalized: true,
+            ..html("/post/hello")
+        };
+        assert_eq!(
+            engine.resolve(&private, None).unwrap().cache_control,
+            "private, no-store"
+        );
+
+        let missing = CacheRequest {
+            status: 404,
+            ..html("/post/gone")
+        };
+        assert!(engine
+            .resolve(&missing, None)
+            .unwrap()
+            .cache_control
+            .contains("s-maxage=60,"));
+    }
+
+    #[test]
+    fn test_per_post_override_and_surrogate_keys() {
+        let engine = CachePolicyEngine::new(CachePolicyConfig::default()).unwrap();
+        let meta = HashMap::from([(
+            CACHE_POLICY_META_KEY.to_string(),
+            Value::String(r#"{"s_maxage": 86400}"#.to_string()),
+        )]);
+        let hints = CacheHints {
+            surrogate_keys: SurrogateKeys::new()
+                .post("42")
+                .post_type("post")
+                .term("category", "7")
+                .post("42")
+                .build(),
+            cache_override: CacheOverride::from_meta(&meta),
+        };
+
+        let headers = engine.resolve(&html("/post/hello"), Some(&hints)).unwrap();
+        assert!(headers.cache_control.contains("s-maxage=86400"));
+        assert_eq!(
+            headers.surrogate_keys,
+            vec!["post:42", "post_type:post", "term:category:7"]
+        );
+
+        let no_store = CacheHints {
+            cache_override: Some(CacheOverride {
+                visibility: Some(CacheVisibility::NoStore),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let headers = engine
+            .resolve(&html("/page/account"), Some(&no_store))
+            .unwrap();
+        assert_eq!(headers.cache_control, "private, no-store");
+        assert!(headers.surrogate_control.is_none());
+    }
+
+    #[test]
+    fn test_glob_patterns() {
+        let glob = |pattern: &str, path: &str| compile_glob(pattern).unwrap().is_match(path);
+        assert!(glob("**/feed", "/feed"));
+        assert!(glob("**/feed", "/2024/05/feed"));
+        assert!(glob("**/feed/*", "/feed/json"));
+        assert!(glob("**/feed/*", "/category/news/feed/atom"));
+        assert!(glob("/post/*", "/post/hello/"));
+        assert!(!glob("/post/*", "/post/hello/world"));
+        assert!(glob("/themes/**", "/themes/a/b/c.js"));
+        assert!(compile_glob("post/*").is_err());
+    }
+}
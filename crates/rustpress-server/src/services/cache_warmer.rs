@@ -0,0 +1,645 @@
+//! Cache Warmer
+//!
+//! Pre-renders popular public URLs after a deployment or a burst of
+//! publishes so the first visitors are not the ones paying for a cold
+//! cache. Warming runs as a background job that requests each URL through
+//! the server's own HTTP stack, so responses pass the page cache middleware
+//! exactly like an anonymous visit and rendering primes the per-theme
+//! template caches on the way.
+//!
+//! URLs come from the analytics daily rollups (top pages over the lookback
+//! window), topped up with the most recent posts when analytics has too
+//! little data, plus the always-warmed paths from the settings. Publishes
+//! are debounced: the first one schedules a run after a short delay and
+//! later ones in that window ride along.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use rustpress_core::error::{Error, Result};
+use rustpress_core::http::HttpClient;
+use rustpress_events::EventBus;
+use rustpress_jobs::{JobHandler, JobPayload, JobQueue};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, RwLock};
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+use super::json_setting::JsonSetting;
+use super::redirects::content_path;
+use super::RenderService;
+
+/// Settings key holding the cache warmer configuration
+pub const CACHE_WARMER_SETTINGS_KEY: &str = "cache_warmer";
+
+/// Stored cache warmer settings
+const CACHE_WARMER_SETTING: JsonSetting<CacheWarmerConfig> =
+    JsonSetting::new(CACHE_WARMER_SETTINGS_KEY, "cache", "cache warmer settings");
+
+/// Most URLs a single run may warm
+pub const MAX_WARM_URLS: usize = 1000;
+
+/// Most concurrent warming requests
+pub const MAX_WARM_CONCURRENCY: usize = 32;
+
+/// User agent of warming requests
+const WARMER_USER_AGENT: &str = "RustPress-CacheWarmer/1.0";
+
+/// Failed URLs kept in a run summary
+const MAX_RECORDED_FAILURES: usize = 20;
+
+/// Cache warmer settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CacheWarmerConfig {
+    pub enabled: bool,
+    /// Warm once the server has started
+    pub warm_on_deploy: bool,
+    /// Warm after posts are published
+    pub warm_on_publish: bool,
+    /// Most URLs warmed per run, including `paths`
+    pub max_urls: usize,
+    /// Requests in flight at once
+    pub concurrency: usize,
+    /// Days of analytics rollups used to rank pages
+    pub lookback_days: u32,
+    /// Delay after startup before warming, so the listener is up
+    pub deploy_delay_secs: u64,
+    /// Delay after a publish; publishes within it share one run
+    pub publish_delay_secs: u64,
+    /// Per-request timeout
+    pub timeout_secs: u64,
+    /// Paths warmed on every run, before the popular ones
+    pub paths: Vec<String>,
+    /// `Accept-Encoding` values to warm; the page cache varies on it by
+    /// default, so each common browser value is a separate entry
+    pub accept_encodings: Vec<String>,
+    /// Base URL requests are sent to; defaults to this server's listener.
+    /// Point it at the CDN to warm the edge as well.
+    pub origin: Option<String>,
+}
+
+impl Default for CacheWarmerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            warm_on_deploy: true,
+            warm_on_publish: true,
+            max_urls: 50,
+            concurrency: 4,
+            lookback_days: 7,
+            deploy_delay_secs: 30,
+            publish_delay_secs: 60,
+            timeout_secs: 30,
+            paths: vec!["/".to_string()],
+            accept_encodings: vec![
+                "gzip, deflate, br, zstd".to_string(),
+                "gzip, deflate, br".to_string(),
+            ],
+            origin: None,
+        }
+    }
+}
+
+impl CacheWarmerConfig {
+    /// Load the configuration from settings, falling back to defaults
+    pub async fn load(pool: &PgPool) -> Result<Self> {
+        CACHE_WARMER_SETTING.load_or_default(pool).await
+    }
+
+    /// Persist the configuration to settings
+    pub async fn save(&self, pool: &PgPool) -> Result<()> {
+        CACHE_WARMER_SETTING.save(pool, self).await
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.max_urls == 0 || self.max_urls > MAX_WARM_URLS {
+            return Err(Error::invalid_input(
+                "max_urls",
+                format!("Must be between 1 and {}", MAX_WARM_URLS),
+            ));
+        }
+        if self.concurrency == 0 || self.concurrency > MAX_WARM_CONCURRENCY {
+            return Err(Error::invalid_input(
+                "concurrency",
+                format!("Must be between 1 and {}", MAX_WARM_CONCURRENCY),
+            ));
+        }
+        if !(1..=90).contains(&self.lookback_days) {
+            return Err(Error::invalid_input(
+                "lookback_days",
+                "Must be between 1 and 90",
+            ));
+        }
+        if self.timeout_secs == 0 {
+            return Err(Error::invalid_input(
+                "timeout_secs",
+                "Must be at least one second",
+            ));
+        }
+        if let Some(path) = self.paths.iter().find(|path| !is_warmable_path(path)) {
+            return Err(Error::invalid_input(
+                "paths",
+                format!("'{}' is not a public site path", path),
+            ));
+        }
+        if let Some(ref origin) = self.origin {
+            if !matches!(reqwest::Url::parse(origin), Ok(url) if url.has_host()) {
+                return Err(Error::invalid_input(
+                    "origin",
+                    "Origin must be an absolute http(s) URL",
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Public paths that can be cached: site-relative, not admin or API
+fn is_warmable_path(path: &str) -> bool {
+    path.starts_with('/')
+        && !path.starts_with("//")
+        && !path.starts_with("/api/")
+        && !path.starts_with("/admin")
+        && !path.contains("preview=")
+        && !path.chars().any(char::is_whitespace)
+}
+
+/// Why a warming run was started
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WarmReason {
+    Deploy,
+    Publish,
+    Manual,
+}
+
+/// Background job warming the page cache
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WarmPageCacheJob {
+    pub reason: WarmReason,
+    /// Paths to warm instead of the popular ones
+    #[serde(default)]
+    pub paths: Vec<String>,
+}
+
+impl JobPayload for WarmPageCacheJob {
+    fn job_type() -> &'static str {
+        "warm_page_cache"
+    }
+
+    // Kept running in read-only mode, unlike the default queue
+    fn queue() -> &'static str {
+        crate::services::read_only::CACHE_QUEUE
+    }
+
+    // Warming again later is better than hammering a struggling server
+    fn max_attempts() -> u32 {
+        1
+    }
+
+    fn timeout_secs() -> u64 {
+        900
+    }
+}
+
+/// Summary of a warming run
+#[derive(Debug, Clone, Serialize)]
+pub struct WarmRun {
+    pub reason: WarmReason,
+    pub started_at: DateTime<Utc>,
+    pub duration_ms: u64,
+    /// Distinct paths warmed
+    pub urls: usize,
+    /// Requests made, one per path and `Accept-Encoding` value
+    pub requests: usize,
+    /// Requests answered with a 2xx status
+    pub warmed: usize,
+    pub failed: usize,
+    /// First failures as `path: reason`
+    pub failures: Vec<String>,
+}
+
+/// Where warming requests go
+#[derive(Debug, Clone)]
+pub struct WarmTarget {
+    /// Listener address the server binds to
+    pub host: String,
+    pub port: u16,
+    pub tls: bool,
+}
+
+impl WarmTarget {
+    fn base_url(&self) -> String {
+        let host = match self.host.as_str() {
+            "0.0.0.0" | "" => "127.0.0.1",
+            "::" | "[::]" => "[::1]",
+            host => host,
+        };
+        let scheme = if self.tls { "https" } else { "http" };
+        format!("{}://{}:{}", scheme, host, self.port)
+    }
+}
+
+/// Schedules and runs page cache warming
+pub struct CacheWarmerService {
+    pool: PgPool,
+    renderer: Arc<RenderService>,
+    target: WarmTarget,
+    http: HttpClient,
+    config: RwLock<Option<Arc<CacheWarmerConfig>>>,
+    /// When the pending debounced run starts (ms since the epoch), or 0
+    pending_until: AtomicI64,
+    last_run: RwLock<Option<WarmRun>>,
+}
+
+impl CacheWarmerService {
+    pub fn new(
+        pool: PgPool,
+        renderer: Arc<RenderService>,
+        target: WarmTarget,
+        http: HttpClient,
+    ) -> Self {
+        Self {
+            pool,
+            renderer,
+            target,
+            http,
+            config: RwLock::new(None),
+            pending_until: AtomicI64::new(0),
+            last_run: RwLock::new(None),
+        }
+    }
+
+    /// Configuration, loaded from settings on first use
+    pub async fn config(&self) -> Arc<CacheWarmerConfig> {
+        if let Some(config) = self.config.read().await.clone() {
+            return config;
+        }
+
+        let config = CacheWarmerConfig::load(&self.pool)
+            .await
+            .unwrap_or_else(|e| {
+                tracing::warn!(
+                    "Failed to load cache warmer settings, using defaults: {}",
+                    e
+                );
+                CacheWarmerConfig::default()
+            });
+        let config = Arc::new(config);
+        *self.config.write().await = Some(config.clone());
+        config
+    }
+
+    /// Validate, persist and apply new settings
+    pub async fn set_config(&self, config: CacheWarmerConfig) -> Result<()> {
+        config.validate()?;
+        config.save(&self.pool).await?;
+        *self.config.write().await = Some(Arc::new(config));
+        Ok(())
+    }
+
+    /// Drop the cached settings; the next use reloads them
+    pub async fn invalidate(&self) {
+        *self.config.write().await = None;
+    }
+
+    /// Summary of the most recent run on this instance
+    pub async fn last_run(&self) -> Option<WarmRun> {
+        self.last_run.read().await.clone()
+    }
+
+    /// Schedule a debounced run after a deploy or publish. Returns the job
+    /// id, or `None` when warming is off for `reason` or a run is already
+    /// pending.
+    pub async fn schedule(&self, queue: &JobQueue, reason: WarmReason) -> Result<Option<Uuid>> {
+        let config = self.config().await;
+        let delay_secs = match reason {
+            WarmReason::Deploy if config.warm_on_deploy => config.deploy_delay_secs,
+            WarmReason::Publish if config.warm_on_publish => config.publish_delay_secs,
+            WarmReason::Manual => 0,
+            _ => return Ok(None),
+        };
+        if !config.enabled {
+            return Ok(None);
+        }
+
+        let now = Utc::now().timestamp_millis();
+        let starts_at = now + delay_secs as i64 * 1000;
+        let pending = self.pending_until.load(Ordering::Acquire);
+        if pending > now
+            || self
+                .pending_until
+                .compare_exchange(pending, starts_at, Ordering::AcqRel, Ordering::Acquire)
+                .is_err()
+        {
+            return Ok(None);
+        }
+
+        let job = WarmPageCacheJob {
+            reason,
+            paths: Vec::new(),
+        };
+        match queue.dispatch_delayed(job, delay_secs).await {
+            Ok(id) => Ok(Some(id)),
+            Err(e) => {
+                self.pending_until.store(0, Ordering::Release);
+                Err(Error::internal(format!(
+                    "Failed to queue cache warming: {}",
+                    e
+                )))
+            }
+        }
+    }
+
+    /// Queue a run now, for the given paths or the popular ones
+    pub async fn enqueue(&self, queue: &JobQueue, paths: Vec<String>) -> Result<Uuid> {
+        if let Some(path) = paths.iter().find(|path| !is_warmable_path(path)) {
+            return Err(Error::invalid_input(
+                "urls",
+                format!("'{}' is not a public site path", path),
+            ));
+        }
+        let job = WarmPageCacheJob {
+            reason: WarmReason::Manual,
+            paths,
+        };
+        queue
+            .dispatch(job)
+            .await
+            .map_err(|e| Error::internal(format!("Failed to queue cache warming: {}", e)))
+    }
+
+    /// Schedule a run whenever a post is published
+    pub fn spawn_publish_listener(
+        self: &Arc<Self>,
+        bus: &EventBus,
+        queue: Arc<JobQueue>,
+    ) -> JoinHandle<()> {
+        let warmer = self.clone();
+        let mut rx = bus.subscribe_broadcast();
+        tokio::spawn(async move {
+            loop {
+                match rx.recv().await {
+                    Ok(event) if event.event_type == "post.published" => {
+                        if let Err(e) = warmer.schedule(&queue, WarmReason::Publish).await {
+                            tracing::warn!("Failed to schedule cache warming: {}", e);
+                        }
+                    }
+                    Ok(_) => {}
+                    // A missed publish only delays warming until the next one
+                    Err(broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        })
+    }
+
+    /// Most viewed paths from the analytics rollups, topped up with recent
+    /// posts, after the configured paths
+    pub async fn popular_paths(&self, config: &CacheWarmerConfig) -> Vec<String> {
+        let mut paths: Vec<String> = Vec::new();
+        let mut push = |path: String| {
+            if paths.len() < config.max_urls && is_warmable_path(&path) && !paths.contains(&path) {
+                paths.push(path);
+            }
+        };
+        config.paths.iter().cloned().for_each(&mut push);
+
+        // The rollups belong to the analytics plugin and may not exist
+        let limit = config.max_urls as i64;
+        let top: Vec<(String,)> = sqlx::query_as(
+            r#"
+            SELECT page->>'page_path' AS path
+            FROM rustanalytics_daily_data d,
+                 jsonb_array_elements(COALESCE(d.pages, '[]'::jsonb)) AS page
+            WHERE d.data_date >= CURRENT_DATE - $1::int
+              AND page ? 'page_path'
+            GROUP BY 1
+            ORDER BY SUM(COALESCE((page->>'pageviews')::bigint, 0)) DESC
+            LIMIT $2
+            "#,
+        )
+        .bind(config.lookback_days as i32)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .unwrap_or_else(|e| {
+            tracing::debug!("No analytics rollups for cache warming: {}", e);
+            Vec::new()
+        });
+        top.into_iter().for_each(|(path,)| push(path));
+
+        let recent: Vec<(String, String)> = sqlx::query_as(
+            r#"
+            SELECT post_type::text, slug FROM posts
+            WHERE status = 'published' AND deleted_at IS NULL
+              AND post_type IN ('post', 'page', 'episode', 'event')
+            ORDER BY published_at DESC NULLS LAST
+            LIMIT $1
+            "#,
+        )
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .unwrap_or_else(|e| {
+            tracing::warn!("Failed to load recent posts for cache warming: {}", e);
+            Vec::new()
+        });
+        recent
+            .into_iter()
+            .for_each(|(post_type, slug)| push(content_path(&post_type, &slug)));
+
+        paths
+    }
+
+    /// Warm `paths`, or the popular paths when empty
+    pub async fn warm(&self, reason: WarmReason, paths: Vec<String>) -> Result<WarmRun> {
+        if reason != WarmReason::Manual {
+            self.pending_until.store(0, Ordering::Release);
+        }
+
+        let config = self.config().await;
+        let started_at = Utc::now();
+        let started = Instant::now();
+
+        let paths = if paths.is_empty() {
+            self.popular_paths(&config).await
+        } else {
+            paths
+        };
+
+        let base = config
+            .origin
+            .clone()
+            .unwrap_or_else(|| self.target.base_url());
+        let base = base.trim_end_matches('/').to_string();
+        // Cache keys include the host visitors use
+        let site_host = reqwest::Url::parse(&self.renderer.site_url().await)
+            .ok()
+            .and_then(|url| {
+                url.host_str().map(|host| match url.port() {
+                    Some(port) => format!("{}:{}", host, port),
+                    None => host.to_string(),
+                })
+            });
+
+        // The local TLS listener's certificate is issued for the public
+        // name, so only requests to it need a client that accepts it; the
+        // rest go through the shared client
+        let local_tls = match config.origin {
+            None if self.target.tls => Some(
+                reqwest::Client::builder()
+                    .redirect(reqwest::redirect::Policy::none())
+                    .danger_accept_invalid_certs(true)
+                    .build()
+                    .map_err(|e| Error::internal(format!("Failed to build HTTP client: {}", e)))?,
+            ),
+            _ => None,
+        };
+
+        let encodings: Vec<Option<String>> = if config.accept_encodings.is_empty() {
+            vec![None]
+        } else {
+            config.accept_encodings.iter().cloned().map(Some).collect()
+        };
+        let mut requests = Vec::with_capacity(paths.len() * encodings.len());
+        for path in &paths {
+            for encoding in &encodings {
+                let url = format!("{}{}", base, path);
+                let mut request = match &local_tls {
+                    Some(client) => client.get(url),
+                    None => self.http.get(url),
+                }
+                .timeout(Duration::from_secs(config.timeout_secs))
+                .header(reqwest::header::USER_AGENT, WARMER_USER_AGENT)
+                .header(reqwest::header::ACCEPT, "text/html,application/xhtml+xml")
+                .header(reqwest::header::ACCEPT_LANGUAGE, "en");
+                if let Some(encoding) = encoding {
+                    request = request.header(reqwest::header::ACCEPT_ENCODING, encoding);
+                }
+                if let (None, Some(host)) = (&config.origin, &site_host) {
+                    request = request.header(reqwest::header::HOST, host);
+                }
+                requests.push((path.clone(), request));
+            }
+        }
+
+        let (http, local_tls) = (&self.http, &local_tls);
+        let results: Vec<std::result::Result<(), String>> = futures::stream::iter(requests)
+            .map(|(path, request)| async move {
+                let response = match local_tls {
+                    Some(_) => request.send().await.map_err(|e| e.to_string()),
+                    None => http.send(request).await.map_err(|e| e.to_string()),
+                }
+                .map_err(|e| format!("{}: {}", path, e))?;
+                let status = response.status();
+                // Read to the end so the response is complete
+                let _ = response.bytes().await;
+                if status.is_success() {
+                    Ok(())
+                } else {
+                    Err(format!("{}: HTTP {}", path, status.as_u16()))
+                }
+            })
+            .buffer_unordered(config.concurrency.max(1))
+            .collect()
+            .await;
+
+        let failures: Vec<String> = results.iter().filter_map(|r| r.clone().err()).collect();
+        let run = WarmRun {
+            reason,
+            started_at,
+            duration_ms: started.elapsed().as_millis() as u64,
+            urls: paths.len(),
+            requests: results.len(),
+            warmed: results.len() - failures.len(),
+            failed: failures.len(),
+            failures: failures.into_iter().take(MAX_RECORDED_FAILURES).collect(),
+        };
+        tracing::info!(
+            reason = ?run.reason,
+            urls = run.urls,
+            warmed = run.warmed,
+            failed = run.failed,
+            duration_ms = run.duration_ms,
+            "Page cache warmed"
+        );
+        *self.last_run.write().await = Some(run.clone());
+        Ok(run)
+    }
+}
+
+/// Handler for [`WarmPageCacheJob`]
+#[derive(Clone)]
+pub struct WarmPageCacheHandler {
+    warmer: Arc<CacheWarmerService>,
+}
+
+impl WarmPageCacheHandler {
+    pub fn new(warmer: Arc<CacheWarmerService>) -> Self {
+        Self { warmer }
+    }
+}
+
+#[async_trait]
+impl JobHandler for WarmPageCacheHandler {
+    type Payload = WarmPageCacheJob;
+
+    async fn handle(&self, payload: Self::Payload) -> Result<()> {
+        if !self.warmer.config().await.enabled && payload.reason != WarmReason::Manual {
+            return Ok(());
+        }
+        self.warmer.warm(payload.reason, payload.paths).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_warmable_paths_and_validation() {
+        assert!(is_warmable_path("/"));
+        assert!(is_warmable_path("/post/hello-world"));
+        assert!(is_warmable_path("/category/news?page=2"));
+        assert!(!is_warmable_path("https://example.com/"));
+        assert!(!is_warmable_path("//evil.example/"));
+        assert!(!is_warmable_path("/admin/posts"));
+        assert!(!is_warmable_path("/api/v1/posts"));
+        assert!(!is_warmable_path("/post/x?preview=abc"));
+
+        assert!(CacheWarmerConfig::default().validate().is_ok());
+        let config = CacheWarmerConfig {
+            concurrency: 0,
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+        let config = CacheWarmerConfig {
+            origin: Some("not a url".to_string()),
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_warm_target_base_url() {
+        let target = WarmTarget {
+            host: "0.0.0.0".to_string(),
+            port: 3000,
+            tls: false,
+        };
+        assert_eq!(target.base_url(), "http://127.0.0.1:3000");
+        let target = WarmTarget {
+            host: "10.0.0.5".to_string(),
+            port: 443,
+            tls: true,
+        };
+        assert_eq!(target.base_url(), "https://10.0.0.5:443");
+    }
+}
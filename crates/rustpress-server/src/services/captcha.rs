@@ -0,0 +1,647 @@
+//! CAPTCHA Verification
+//!
+//! Server-side verification for Cloudflare Turnstile, hCaptcha and
+//! reCAPTCHA v3, the last line of defence after the built-in anti-abuse
+//! challenges. Each protected endpoint opts in separately and can set its
+//! own score threshold and expected action. When the provider cannot be
+//! reached, submissions are let through (and logged) unless the site is
+//! configured to fail closed.
+
+use async_trait::async_trait;
+use rustpress_core::error::{Error, Result};
+use rustpress_core::http::HttpClient;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+
+use super::json_setting::JsonSetting;
+
+/// Settings key holding the CAPTCHA configuration
+pub const CAPTCHA_SETTINGS_KEY: &str = "captcha_config";
+
+/// Stored CAPTCHA settings
+const CAPTCHA_SETTING: JsonSetting<CaptchaConfig> =
+    JsonSetting::new(CAPTCHA_SETTINGS_KEY, "security", "CAPTCHA settings");
+
+/// Body field clients may use for the token regardless of provider
+pub const CAPTCHA_FIELD: &str = "_captcha";
+
+/// Placeholder returned instead of the stored secret key
+const MASKED_SECRET: &str = "********";
+
+/// Supported CAPTCHA providers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptchaProvider {
+    #[default]
+    Turnstile,
+    Hcaptcha,
+    Recaptcha,
+}
+
+impl CaptchaProvider {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CaptchaProvider::Turnstile => "turnstile",
+            CaptchaProvider::Hcaptcha => "hcaptcha",
+            CaptchaProvider::Recaptcha => "recaptcha",
+        }
+    }
+
+    /// Siteverify endpoint of the provider
+    pub fn verify_url(&self) -> &'static str {
+        match self {
+            CaptchaProvider::Turnstile => {
+                "https://challenges.cloudflare.com/turnstile/v0/siteverify"
+            }
+            CaptchaProvider::Hcaptcha => "https://api.hcaptcha.com/siteverify",
+            CaptchaProvider::Recaptcha => "https://www.google.com/recaptcha/api/siteverify",
+        }
+    }
+
+    /// Form field the provider's widget submits the token in
+    pub fn response_field(&self) -> &'static str {
+        match self {
+            CaptchaProvider::Turnstile => "cf-turnstile-response",
+            CaptchaProvider::Hcaptcha => "h-captcha-response",
+            CaptchaProvider::Recaptcha => "g-recaptcha-response",
+        }
+    }
+}
+
+/// Result of a provider verification call
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CaptchaVerification {
+    pub success: bool,
+    /// Risk score (reCAPTCHA v3, hCaptcha Enterprise); 1.0 is most likely human
+    #[serde(default)]
+    pub score: Option<f64>,
+    #[serde(default)]
+    pub action: Option<String>,
+    #[serde(default)]
+    pub hostname: Option<String>,
+    #[serde(default, rename = "error-codes")]
+    pub error_codes: Vec<String>,
+}
+
+/// Provider could not be asked (network error, timeout, bad response)
+#[derive(Debug, Clone)]
+pub struct CaptchaUnavailable(pub String);
+
+impl fmt::Display for CaptchaUnavailable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CAPTCHA provider unavailable: {}", self.0)
+    }
+}
+
+/// Verifies CAPTCHA tokens with a provider
+#[async_trait]
+pub trait CaptchaVerifier: Send + Sync {
+    fn provider(&self) -> CaptchaProvider;
+
+    async fn verify(
+        &self,
+        token: &str,
+        remote_ip: Option<&str>,
+    ) -> std::result::Result<CaptchaVerification, CaptchaUnavailable>;
+}
+
+/// Shared siteverify call; all three providers use the same form protocol
+async fn siteverify(
+    client: &HttpClient,
+    url: &str,
+    secret: &str,
+    token: &str,
+    remote_ip: Option<&str>,
+) -> std::result::Result<CaptchaVerification, CaptchaUnavailable> {
+    let mut form = vec![("secret", secret), ("response", token)];
+    if let Some(ip) = remote_ip {
+        form.push(("remoteip", ip));
+    }
+
+    let response = client
+        .send(client.post(url).form(&form))
+        .await
+        .map_err(|e| CaptchaUnavailable(e.to_string()))?;
+    if response.status().is_server_error() {
+        return Err(CaptchaUnavailable(format!(
+            "provider returned {}",
+            response.status()
+        )));
+    }
+
+    response
+        .json::<CaptchaVerification>()
+        .await
+        .map_err(|e| CaptchaUnavailable(format!("invalid provider response: {}", e)))
+}
+
+/// Cloudflare Turnstile driver
+pub struct TurnstileVerifier {
+    client: HttpClient,
+    secret: String,
+}
+
+impl TurnstileVerifier {
+    pub fn new(client: HttpClient, secret: impl Into<String>) -> Self {
+        Self {
+            client,
+            secret: secret.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl CaptchaVerifier for TurnstileVerifier {
+    fn provider(&self) -> CaptchaProvider {
+        CaptchaProvider::Turnstile
+    }
+
+    async fn verify(
+        &self,
+        token: &str,
+        remote_ip: Option<&str>,
+    ) -> std::result::Result<CaptchaVerification, CaptchaUnavailable> {
+        let url = self.provider().verify_url();
+        siteverify(&self.client, url, &self.secret, token, remote_ip).await
+    }
+}
+
+/// hCaptcha driver
+pub struct HcaptchaVerifier {
+    client: HttpClient,
+    secret: String,
+}
+
+impl HcaptchaVerifier {
+    pub fn new(client: HttpClient, secret: impl Into<String>) -> Self {
+        Self {
+            client,
+            secret: secret.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl CaptchaVerifier for HcaptchaVerifier {
+    fn provider(&self) -> CaptchaProvider {
+        CaptchaProvider::Hcaptcha
+    }
+
+    async fn verify(
+        &self,
+        token: &str,
+        remote_ip: Option<&str>,
+    ) -> std::result::Result<CaptchaVerification, CaptchaUnavailable> {
+        let url = self.provider().verify_url();
+        let mut verification =
+            siteverify(&self.client, url, &self.secret, token, remote_ip).await?;
+        // hCaptcha Enterprise reports risk (0.0 safe, 1.0 bot); normalise so
+        // higher always means more likely human
+        if let Some(score) = verification.score {
+            verification.score = Some(1.0 - score);
+        }
+        Ok(verification)
+    }
+}
+
+/// Google reCAPTCHA v3 driver
+pub struct RecaptchaV3Verifier {
+    client: HttpClient,
+    secret: String,
+}
+
+impl RecaptchaV3Verifier {
+    pub fn new(client: HttpClient, secret: impl Into<String>) -> Self {
+        Self {
+            client,
+            secret: secret.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl CaptchaVerifier for RecaptchaV3Verifier {
+    fn provider(&self) -> CaptchaProvider {
+        CaptchaProvider::Recaptcha
+    }
+
+    async fn verify(
+        &self,
+        token: &str,
+        remote_ip: Option<&str>,
+    ) -> std::result::Result<CaptchaVerification, CaptchaUnavailable> {
+        let url = self.provider().verify_url();
+        siteverify(&self.client, url, &self.secret, token, remote_ip).await
+    }
+}
+
+/// CAPTCHA settings for one endpoint
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EndpointCaptcha {
+    pub enabled: bool,
+    /// Minimum score for score-based providers (overrides the default)
+    pub min_score: Option<f64>,
+    /// Action the token must have been issued for (reCAPTCHA v3)
+    pub action: Option<String>,
+}
+
+/// CAPTCHA configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CaptchaConfig {
+    pub enabled: bool,
+    pub provider: CaptchaProvider,
+    /// Public key rendered into the widget
+    pub site_key: String,
+    pub secret_key: String,
+    /// Minimum score when an endpoint does not set its own
+    pub min_score: f64,
+    /// Let submissions through when the provider is unreachable
+    pub fail_open: bool,
+    pub timeout_ms: u64,
+    pub endpoints: HashMap<String, EndpointCaptcha>,
+}
+
+impl Default for CaptchaConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            provider: CaptchaProvider::default(),
+            site_key: String::new(),
+            secret_key: String::new(),
+            min_score: 0.5,
+            fail_open: true,
+            timeout_ms: 3000,
+            endpoints: HashMap::new(),
+        }
+    }
+}
+
+impl CaptchaConfig {
+    /// Load the configuration from settings, falling back to defaults
+    pub async fn load(pool: &PgPool) -> Result<Self> {
+        CAPTCHA_SETTING.load_or_default(pool).await
+    }
+
+    /// Persist the configuration to settings
+    pub async fn save(&self, pool: &PgPool) -> Result<()> {
+        CAPTCHA_SETTING.save(pool, self).await
+    }
+
+    /// Validate keys and thresholds
+    pub fn validate(&self) -> Result<()> {
+        if self.enabled && (self.site_key.is_empty() || self.secret_key.is_empty()) {
+            return Err(Error::invalid_input(
+                "captcha",
+                "Site key and secret key are required to enable CAPTCHA",
+            ));
+        }
+
+        let scores = std::iter::once(self.min_score)
+            .chain(self.endpoints.values().filter_map(|e| e.min_score));
+        for score in scores {
+            if !(0.0..=1.0).contains(&score) {
+                return Err(Error::invalid_input(
+                    "min_score",
+                    "Scores must be between 0.0 and 1.0",
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Copy safe to return from the API, with the secret key masked
+    pub fn masked(&self) -> Self {
+        let mut config = self.clone();
+        if !config.secret_key.is_empty() {
+            config.secret_key = MASKED_SECRET.to_string();
+        }
+        config
+    }
+
+    /// Keep the stored secret when an update sends the mask back unchanged
+    pub fn merge_secret(&mut self, current: &CaptchaConfig) {
+        if self.secret_key == MASKED_SECRET {
+            self.secret_key = current.secret_key.clone();
+        }
+    }
+
+    /// Settings for an endpoint, if CAPTCHA is required there
+    pub fn endpoint(&self, endpoint: &str) -> Option<&EndpointCaptcha> {
+        if !self.enabled {
+            return None;
+        }
+        self.endpoints.get(endpoint).filter(|e| e.enabled)
+    }
+
+    /// Build the driver for the configured provider
+    pub fn verifier(&self, client: HttpClient) -> Box<dyn CaptchaVerifier> {
+        let secret = self.secret_key.clone();
+        match self.provider {
+            CaptchaProvider::Turnstile => Box::new(TurnstileVerifier::new(client, secret)),
+            CaptchaProvider::Hcaptcha => Box::new(HcaptchaVerifier::new(client, secret)),
+            CaptchaProvider::Recaptcha => Box::new(RecaptchaV3Verifier::new(client, secret)),
+        }
+    }
+
+    /// Turn a provider answer into a decision for an endpoint
+    pub fn evaluate(
+        &self,
+        settings: &EndpointCaptcha,
+        result: std::result::Result<CaptchaVerification, CaptchaUnavailable>,
+    ) -> std::result::Result<CaptchaOutcome, CaptchaFailure> {
+        let verification = match result {
+            Ok(verification) => verification,
+            Err(e) if self.fail_open => {
+                tracing::warn!("{}; accepting submission without CAPTCHA", e);
+                return Ok(CaptchaOutcome::Degraded);
+            }
+            Err(e) => {
+                tracing::error!("{}; rejecting submission", e);
+                return Err(CaptchaFailure::Unavailable);
+            }
+        };
+
+        if !verification.success {
+            return Err(CaptchaFailure::Rejected(verification.error_codes));
+        }
+        if let Some(expected) = &settings.action {
+            if verification.action.as_deref() != Some(expected.as_str()) {
+                return Err(CaptchaFailure::ActionMismatch);
+            }
+        }
+        if let Some(score) = verification.score {
+            let threshold = settings.min_score.unwrap_or(self.min_score);
+            if score < threshold {
+                return Err(CaptchaFailure::LowScore(score));
+            }
+        }
+
+        Ok(CaptchaOutcome::Verified)
+    }
+}
+
+/// Accepted submission
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptchaOutcome {
+    /// CAPTCHA is not required for the endpoint
+    NotRequired,
+    Verified,
+    /// Provider unreachable, accepted because the site fails open
+    Degraded,
+}
+
+/// Why a submission was rejected
+#[derive(Debug, Clone, PartialEq)]
+pub enum CaptchaFailure {
+    MissingToken,
+    Rejected(Vec<String>),
+    LowScore(f64),
+    ActionMismatch,
+    Unavailable,
+}
+
+impl CaptchaFailure {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CaptchaFailure::MissingToken => "missing_token",
+            CaptchaFailure::Rejected(_) => "rejected",
+            CaptchaFailure::LowScore(_) => "low_score",
+            CaptchaFailure::ActionMismatch => "action_mismatch",
+            CaptchaFailure::Unavailable => "unavailable",
+        }
+    }
+}
+
+impl fmt::Display for CaptchaFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CaptchaFailure::MissingToken => f.write_str("CAPTCHA response is required"),
+            CaptchaFailure::Unavailable => {
+                f.write_str("CAPTCHA verification is temporarily unavailable")
+            }
+            _ => f.write_str("CAPTCHA verification failed"),
+        }
+    }
+}
+
+/// Public widget settings for an endpoint
+#[derive(Debug, Clone, Serialize)]
+pub struct CaptchaWidget {
+    pub endpoint: String,
+    pub required: bool,
+    pub provider: Option<CaptchaProvider>,
+    pub site_key: Option<String>,
+    pub action: Option<String>,
+    pub response_field: Option<&'static str>,
+}
+
+/// Verifies CAPTCHA tokens for protected endpoints
+pub struct CaptchaService {
+    pool: PgPool,
+    client: HttpClient,
+    config: RwLock<Option<Arc<CaptchaConfig>>>,
+}
+
+impl CaptchaService {
+    pub fn new(pool: PgPool, client: HttpClient) -> Self {
+        Self {
+            pool,
+            client,
+            config: RwLock::new(None),
+        }
+    }
+
+    /// CAPTCHA configuration, loaded from settings on first use
+    pub async fn config(&self) -> Arc<CaptchaConfig> {
+        if let Some(config) = self.config.read().await.clone() {
+            return config;
+        }
+
+        match CaptchaConfig::load(&self.pool).await {
+            Ok(config) => {
+                let config = Arc::new(config);
+                *self.config.write().await = Some(config.clone());
+                config
+            }
+            Err(e) => {
+                tracing::warn!("Failed to load CAPTCHA settings, disabling CAPTCHA: {}", e);
+                Arc::new(CaptchaConfig::default())
+            }
+        }
+    }
+
+    /// Replace the cached configuration after it was saved
+    pub async fn set_config(&self, config: CaptchaConfig) {
+        *self.config.write().await = Some(Arc::new(config));
+    }
+
+    /// Drop the cached configuration; the next use reloads it
+    pub async fn invalidate(&self) {
+        *self.config.write().await = None;
+    }
+
+    /// Widget settings a form needs to render the CAPTCHA
+    pub async fn widget(&self, endpoint: &str) -> CaptchaWidget {
+        let config = self.config().await;
+        let settings = config.endpoint(endpoint);
+        CaptchaWidget {
+            endpoint: endpoint.to_string(),
+            required: settings.is_some(),
+            provider: settings.map(|_| config.provider),
+            site_key: settings.map(|_| config.site_key.clone()),
+            action: settings.and_then(|s| s.action.clone()),
+            response_field: settings.map(|_| config.provider.response_field()),
+        }
+    }
+
+    /// Whether an endpoint requires a CAPTCHA
+    pub async fn is_required(&self, endpoint: &str) -> bool {
+        self.config().await.endpoint(endpoint).is_some()
+    }
+
+    /// Verify the CAPTCHA token in a submission body
+    pub async fn verify(
+        &self,
+        endpoint: &str,
+        body: &Value,
+        remote_ip: Option<&str>,
+    ) -> std::result::Result<CaptchaOutcome, CaptchaFailure> {
+        let config = self.config().await;
+        let Some(settings) = config.endpoint(endpoint) else {
+            return Ok(CaptchaOutcome::NotRequired);
+        };
+
+        let token = captcha_token(body, config.provider).ok_or(CaptchaFailure::MissingToken)?;
+        let verifier = config.verifier(self.client.clone());
+        let result = match tokio::time::timeout(
+            Duration::from_millis(config.timeout_ms),
+            verifier.verify(token, remote_ip),
+        )
+        .await
+        {
+            Ok(result) => result,
+            Err(_) => Err(CaptchaUnavailable("timed out".to_string())),
+        };
+
+        let outcome = config.evaluate(settings, result);
+        if let Err(failure) = &outcome {
+            tracing::info!(
+                endpoint = %endpoint,
+                provider = config.provider.as_str(),
+                reason = failure.as_str(),
+                "Rejected submission failing CAPTCHA"
+            );
+        }
+        outcome
+    }
+}
+
+/// Token from `_captcha` or the provider's own response field
+pub fn captcha_token(body: &Value, provider: CaptchaProvider) -> Option<&str> {
+    [CAPTCHA_FIELD, provider.response_field()]
+        .iter()
+        .find_map(|field| body.get(*field).and_then(Value::as_str))
+        .filter(|token| !token.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn config() -> CaptchaConfig {
+        CaptchaConfig {
+            enabled: true,
+            provider: CaptchaProvider::Recaptcha,
+            site_key: "site".into(),
+            secret_key: "secret".into(),
+            endpoints: HashMap::from([(
+                "comment".to_string(),
+                EndpointCaptcha {
+                    enabled: true,
+                    min_score: Some(0.7),
+                    action: Some("comment".into()),
+                },
+            )]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_provider_response_and_token() {
+        let verification: CaptchaVerification = serde_json::from_value(json!({
+            "success": false,
+            "challenge_ts": "2024-05-01T10:00:00Z",
+            "error-codes": ["invalid-input-response"]
+        }))
+        .unwrap();
+        assert!(!verification.success);
+        assert_eq!(verification.error_codes, vec!["invalid-input-response"]);
+
+        let body = json!({ "g-recaptcha-response": "abc" });
+        assert_eq!(
+            captcha_token(&body, CaptchaProvider::Recaptcha),
+            Some("abc")
+        );
+        assert_eq!(captcha_token(&body, CaptchaProvider::Turnstile), None);
+        let body = json!({ "_captcha": "xyz" });
+        assert_eq!(captcha_token(&body, CaptchaProvider::Hcaptcha), Some("xyz"));
+    }
+
+    #[test]
+    fn test_score_threshold_and_action() {
+        let config = config();
+        let settings = config.endpoint("comment").unwrap().clone();
+        assert!(config.endpoint("registration").is_none());
+
+        let verdict = |score: f64, action: &str| {
+            config.evaluate(
+                &settings,
+                Ok(CaptchaVerification {
+                    success: true,
+                    score: Some(score),
+                    action: Some(action.to_string()),
+                    ..Default::default()
+                }),
+            )
+        };
+        assert_eq!(verdict(0.9, "comment"), Ok(CaptchaOutcome::Verified));
+        assert_eq!(verdict(0.6, "comment"), Err(CaptchaFailure::LowScore(0.6)));
+        assert_eq!(verdict(0.9, "login"), Err(CaptchaFailure::ActionMismatch));
+    }
+
+    #[test]
+    fn test_graceful_degradation_and_secret_masking() {
+        let mut config = config();
+        let settings = config.endpoint("comment").unwrap().clone();
+        let down = || Err(CaptchaUnavailable("connection refused".into()));
+
+        assert_eq!(
+            config.evaluate(&settings, down()),
+            Ok(CaptchaOutcome::Degraded)
+        );
+        config.fail_open = false;
+        assert_eq!(
+            config.evaluate(&settings, down()),
+            Err(CaptchaFailure::Unavailable)
+        );
+
+        let masked = config.masked();
+        assert_eq!(masked.secret_key, MASKED_SECRET);
+        let mut update = masked.clone();
+        update.merge_secret(&config);
+        assert_eq!(update.secret_key, "secret");
+
+        config.min_score = 1.5;
+        assert!(config.validate().is_err());
+    }
+}
@@ -0,0 +1,344 @@
+//! Live change feed built on Postgres LISTEN/NOTIFY.
+//!
+//! Triggers on the content tables announce every committed write on
+//! [`CHANGES_CHANNEL`]. Each node listens and republishes the changes on
+//! the `EventBus` as `change.<entity>.<op>` events (`change.post.updated`),
+//! which reach dashboard sockets subscribed to `change.*` through the live
+//! notification bridge. Writes made on any node show up everywhere without
+//! polling.
+//!
+//! Notifications are queued between the listener and the bus. When the
+//! queue is full, or the listener had to reconnect, changes may have been
+//! missed and a [`RESYNC_EVENT`] tells consumers to reload what they show.
+
+use rustpress_core::error::{Error, Result};
+use rustpress_events::{DomainEvent, EventBus};
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgListener;
+use sqlx::PgPool;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+/// Postgres notification channel content writes are announced on
+pub const CHANGES_CHANNEL: &str = "rustpress_changes";
+
+/// Event published when changes may have been missed
+pub const RESYNC_EVENT: &str = "change.resync";
+
+/// Longest wait between listener reconnection attempts
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// Changes queued for publishing before new ones are dropped
+const QUEUE_SIZE: usize = 1024;
+
+/// Kind of write
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeOp {
+    Insert,
+    Update,
+    Delete,
+}
+
+impl ChangeOp {
+    /// Verb used in event types
+    pub fn as_past(&self) -> &'static str {
+        match self {
+            Self::Insert => "created",
+            Self::Update => "updated",
+            Self::Delete => "deleted",
+        }
+    }
+}
+
+/// A committed write to a watched table
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RowChange {
+    pub table: String,
+    pub op: ChangeOp,
+    pub id: Option<Uuid>,
+    #[serde(default)]
+    pub tenant_id: Option<Uuid>,
+}
+
+impl RowChange {
+    /// Parse a notification payload sent by the triggers
+    pub fn parse(payload: &str) -> Option<Self> {
+        serde_json::from_str(payload).ok()
+    }
+
+    /// Singular entity name of the table, e.g. `post` for `posts`
+    pub fn entity(&self) -> &str {
+        match self.table.as_str() {
+            "media" => "media",
+            "categories" => "category",
+            table => table.strip_suffix('s').unwrap_or(table),
+        }
+    }
+
+    /// Event type the change is published as
+    pub fn event_type(&self) -> String {
+        format!("change.{}.{}", self.entity(), self.op.as_past())
+    }
+
+    pub fn to_event(&self) -> DomainEvent {
+        let event = DomainEvent::new(
+            self.event_type(),
+            serde_json::json!({
+                "table": self.table,
+                "op": self.op,
+                "id": self.id,
+            }),
+        );
+        let event = match self.id {
+            Some(id) => event.with_aggregate(id, self.entity()),
+            None => event,
+        };
+        match self.tenant_id {
+            Some(tenant_id) => event.with_tenant(tenant_id),
+            None => event,
+        }
+    }
+}
+
+/// What the publisher is asked to send
+#[derive(Debug)]
+enum FeedMessage {
+    Change(RowChange),
+    Resync(&'static str),
+}
+
+/// Change feed counters
+#[derive(Debug, Clone, Serialize)]
+pub struct ChangeFeedStats {
+    pub connected: bool,
+    pub received: u64,
+    pub published: u64,
+    pub dropped: u64,
+    pub resyncs: u64,
+}
+
+/// Republishes database writes announced by any node on the event bus
+pub struct ChangeFeed {
+    pool: PgPool,
+    bus: Arc<EventBus>,
+    connected: AtomicBool,
+    /// Set when a change was dropped and consumers have not been told yet
+    overflowed: AtomicBool,
+    received: AtomicU64,
+    published: AtomicU64,
+    dropped: AtomicU64,
+    resyncs: AtomicU64,
+}
+
+impl ChangeFeed {
+    pub fn new(pool: PgPool, bus: Arc<EventBus>) -> Self {
+        Self {
+            pool,
+            bus,
+            connected: AtomicBool::new(false),
+            overflowed: AtomicBool::new(false),
+            received: AtomicU64::new(0),
+            published: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+            resyncs: AtomicU64::new(0),
+        }
+    }
+
+    pub fn stats(&self) -> ChangeFeedStats {
+        ChangeFeedStats {
+            connected: self.connected.load(Ordering::Relaxed),
+            received: self.received.load(Ordering::Relaxed),
+            published: self.published.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+            resyncs: self.resyncs.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Publish changes announced by any node
+    ///
+    /// The listener reconnects with backoff and stops once the feed is
+    /// dropped; the publisher stops with it.
+    pub fn spawn(self: &Arc<Self>) -> tokio::task::JoinHandle<()> {
+        let (tx, rx) = mpsc::channel(QUEUE_SIZE);
+        tokio::spawn(publish(Arc::downgrade(self), rx));
+
+        let feed = Arc::downgrade(self);
+        let pool = self.pool.clone();
+        tokio::spawn(async move {
+            let mut delay = Duration::from_secs(1);
+            let mut reconnected = false;
+            loop {
+                match listen(&feed, &pool, &tx, reconnected).await {
+                    Ok(()) => return,
+                    Err(e) => tracing::warn!("Change feed listener disconnected: {}", e),
+                }
+                if let Some(feed) = feed.upgrade() {
+                    feed.connected.store(false, Ordering::Relaxed);
+                }
+                tokio::time::sleep(delay).await;
+                delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+                reconnected = true;
+            }
+        })
+    }
+
+    /// Queue a change without waiting; a full queue drops it and marks
+    /// the feed for a resync
+    fn enqueue(&self, tx: &mpsc::Sender<FeedMessage>, change: RowChange) {
+        self.received.fetch_add(1, Ordering::Relaxed);
+        if let Err(mpsc::error::TrySendError::Full(_)) = tx.try_send(FeedMessage::Change(change)) {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            if !self.overflowed.swap(true, Ordering::Relaxed) {
+                tracing::warn!("Change feed queue is full, dropping changes until it drains");
+            }
+        }
+    }
+
+    async fn send(&self, message: FeedMessage) {
+        let event = match message {
+            FeedMessage::Change(change) => change.to_event(),
+            FeedMessage::Resync(reason) => {
+                self.resyncs.fetch_add(1, Ordering::Relaxed);
+                DomainEvent::new(RESYNC_EVENT, serde_json::json!({ "reason": reason }))
+            }
+        };
+        let event_type = event.event_type.clone();
+        match self.bus.publish(event).await {
+            Ok(()) => {
+                self.published.fetch_add(1, Ordering::Relaxed);
+            }
+            Err(e) => tracing::warn!(%event_type, "Failed to publish change: {}", e),
+        }
+    }
+}
+
+/// Publish queued changes, following an overflow with a resync once the
+/// queue has drained
+async fn publish(feed: Weak<ChangeFeed>, mut rx: mpsc::Receiver<FeedMessage>) {
+    while let Some(message) = rx.recv().await {
+        let Some(feed) = feed.upgrade() else {
+            return;
+        };
+        feed.send(message).await;
+        if rx.is_empty() && feed.overflowed.swap(false, Ordering::Relaxed) {
+            feed.send(FeedMessage::Resync("overflow")).await;
+        }
+    }
+}
+
+/// Listen on the changes channel and queue notifications until the
+/// connection drops (`Err`) or the feed is gone (`Ok`)
+async fn listen(
+    feed: &Weak<ChangeFeed>,
+    pool: &PgPool,
+    tx: &mpsc::Sender<FeedMessage>,
+    reconnected: bool,
+) -> Result<()> {
+    let listen_error = |e| Error::database_with_source("Change feed listener failed", e);
+    let mut listener = PgListener::connect_with(pool).await.map_err(listen_error)?;
+    listener
+        .listen(CHANGES_CHANNEL)
+        .await
+        .map_err(listen_error)?;
+
+    match feed.upgrade() {
+        Some(feed) => feed.connected.store(true, Ordering::Relaxed),
+        None => return Ok(()),
+    }
+    if reconnected && tx.send(FeedMessage::Resync("reconnected")).await.is_err() {
+        return Ok(());
+    }
+
+    loop {
+        // `None` means the connection was lost; reconnect here so the
+        // resync is only sent once listening again
+        let Some(notification) = listener.try_recv().await.map_err(listen_error)? else {
+            return Err(Error::database("connection lost"));
+        };
+        let Some(feed) = feed.upgrade() else {
+            return Ok(());
+        };
+        match RowChange::parse(notification.payload()) {
+            Some(change) => feed.enqueue(tx, change),
+            None => tracing::debug!("Ignoring malformed change notification"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed(bus: Arc<EventBus>) -> Arc<ChangeFeed> {
+        Arc::new(ChangeFeed::new(
+            PgPool::connect_lazy("postgres://localhost/rustpress_test").unwrap(),
+            bus,
+        ))
+    }
+
+    #[test]
+    fn test_parse_trigger_payload() {
+        let tenant = Uuid::new_v4();
+        let id = Uuid::new_v4();
+        let payload = format!(
+            r#"{{"table" : "posts", "op" : "update", "id" : "{}", "tenant_id" : "{}"}}"#,
+            id, tenant
+        );
+        let change = RowChange::parse(&payload).unwrap();
+        assert_eq!(change.event_type(), "change.post.updated");
+
+        let event = change.to_event();
+        assert_eq!(event.tenant_id, Some(tenant));
+        assert_eq!(event.aggregate_id, Some(id));
+
+        let media = RowChange::parse(r#"{"table":"media","op":"delete","id":null}"#).unwrap();
+        assert_eq!(media.event_type(), "change.media.deleted");
+        let category =
+            RowChange::parse(r#"{"table":"categories","op":"insert","id":null}"#).unwrap();
+        assert_eq!(category.event_type(), "change.category.created");
+
+        assert!(RowChange::parse("setting:blogname").is_none());
+        assert!(RowChange::parse(r#"{"table":"posts","op":"truncate"}"#).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_overflow_is_followed_by_resync() {
+        let bus = Arc::new(EventBus::new());
+        let mut events = bus.subscribe_broadcast();
+        let feed = feed(bus);
+
+        // Fill the queue before the publisher runs
+        let (tx, rx) = mpsc::channel(2);
+        for _ in 0..3 {
+            feed.enqueue(
+                &tx,
+                RowChange {
+                    table: "posts".to_string(),
+                    op: ChangeOp::Insert,
+                    id: Some(Uuid::new_v4()),
+                    tenant_id: None,
+                },
+            );
+        }
+        drop(tx);
+        publish(Arc::downgrade(&feed), rx).await;
+
+        let types: Vec<String> = std::iter::from_fn(|| events.try_recv().ok())
+            .map(|event| event.event_type.clone())
+            .collect();
+        assert_eq!(
+            types,
+            ["change.post.created", "change.post.created", RESYNC_EVENT]
+        );
+
+        let stats = feed.stats();
+        assert_eq!(stats.received, 3);
+        assert_eq!(stats.dropped, 1);
+        assert_eq!(stats.published, 3);
+        assert_eq!(stats.resyncs, 1);
+    }
+}
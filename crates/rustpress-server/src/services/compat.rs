@@ -0,0 +1,88 @@
+//! Compatibility Audits
+//!
+//! Works out which optional features this installation provides — the
+//! pgvector and pg_trgm PostgreSQL extensions, Redis, object storage — so
+//! the plugin and theme managers can refuse extensions that need something
+//! missing, and audits every installed extension for `rustpress doctor
+//! compat`.
+
+use rustpress_core::compat::{features, CompatReport, Platform};
+use rustpress_core::config::AppConfig;
+use rustpress_core::plugin::PluginManager;
+use rustpress_jobs::JobQueue;
+use rustpress_storage::Storage;
+use serde::Serialize;
+use sqlx::PgPool;
+
+use super::ThemeService;
+
+/// PostgreSQL extensions that map to platform features
+const PG_EXTENSIONS: &[(&str, &str)] = &[
+    ("vector", features::PGVECTOR),
+    ("pg_trgm", features::PG_TRGM),
+];
+
+/// Detect the platform from the database, cache, job queue and storage
+/// configuration. Extensions that can't be queried are treated as missing
+pub async fn detect_platform(
+    pool: &PgPool,
+    config: &AppConfig,
+    jobs: &JobQueue,
+    storage: &Storage,
+) -> Platform {
+    let mut platform = Platform::current();
+
+    let names: Vec<&str> = PG_EXTENSIONS.iter().map(|(name, _)| *name).collect();
+    match sqlx::query_scalar::<_, String>(
+        "SELECT extname::text FROM pg_extension WHERE extname = ANY($1)",
+    )
+    .bind(&names)
+    .fetch_all(pool)
+    .await
+    {
+        Ok(installed) => {
+            for (name, feature) in PG_EXTENSIONS {
+                if installed.iter().any(|ext| ext == name) {
+                    platform = platform.with_feature(*feature);
+                }
+            }
+        }
+        Err(e) => tracing::warn!("Failed to list PostgreSQL extensions: {}", e),
+    }
+
+    if config.cache.redis_url.is_some() || jobs.backend_name() == "redis" {
+        platform = platform.with_feature(features::REDIS);
+    }
+    if storage.backend_name() != "local" {
+        platform = platform.with_feature(features::OBJECT_STORAGE);
+    }
+
+    platform
+}
+
+/// Compatibility of everything installed
+#[derive(Debug, Clone, Serialize)]
+pub struct CompatAudit {
+    pub platform: Platform,
+    pub compatible: bool,
+    pub plugins: Vec<CompatReport>,
+    pub themes: Vec<CompatReport>,
+}
+
+/// Check every installed plugin and theme. Themes are checked against the
+/// active plugins as well as the platform
+pub fn audit(plugins: &PluginManager, themes: &ThemeService) -> CompatAudit {
+    let plugin_reports = plugins.audit_compatibility();
+    let theme_reports = themes.audit_compatibility(&plugins.active_profiles());
+    let compatible = plugin_reports
+        .iter()
+        .chain(&theme_reports)
+        .all(CompatReport::is_compatible);
+
+    CompatAudit {
+        platform: plugins.platform(),
+        compatible,
+        plugins: plugin_reports,
+        themes: theme_reports,
+    }
+}
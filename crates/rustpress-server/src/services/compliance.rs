@@ -0,0 +1,709 @@
+//! Regional Content Compliance Rules
+//!
+//! Evaluates per-jurisdiction rules against the visitor's GeoIP location
+//! and the content being served: which cookie banner variant to show,
+//! whether flagged content sits behind an age gate, and whether content
+//! must be withheld in a region (HTTP 451).
+//!
+//! Regions are written as `*` (everywhere), `EU` (European Union
+//! members), an ISO country code (`DE`), a country and subdivision
+//! (`US/California`) or `unknown` (location could not be resolved).
+//! Content is matched by the surrogate keys a rendered page carries
+//! (`post:{id}`, `post_type:{type}`, `term:{taxonomy}:{id}`) or by flags
+//! set in the post's `content_flags` meta.
+//!
+//! Every saved rule set gets a new revision recorded with its author, so
+//! the history of what applied when can be audited, and rules can be
+//! dry-run for a given location through [`ComplianceEngine::explain`].
+
+use chrono::{DateTime, Utc};
+use rustpress_core::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::PgPool;
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use super::geoip::{GeoIpService, GeoLocation};
+use super::json_setting::JsonSetting;
+
+/// Settings key holding the compliance rule set
+pub const COMPLIANCE_SETTINGS_KEY: &str = "compliance_rules";
+
+/// Settings key holding previous revisions of the rule set
+pub const COMPLIANCE_HISTORY_KEY: &str = "compliance_rules_history";
+
+/// Stored rule set
+const COMPLIANCE_SETTING: JsonSetting<ComplianceConfig> =
+    JsonSetting::new(COMPLIANCE_SETTINGS_KEY, "privacy", "compliance rules");
+
+/// Stored previous revisions, newest first
+const COMPLIANCE_HISTORY: JsonSetting<Vec<ComplianceRevision>> =
+    JsonSetting::new(COMPLIANCE_HISTORY_KEY, "privacy", "compliance history");
+
+/// Post meta key listing content flags (e.g. `["adult"]`)
+pub const CONTENT_FLAGS_META_KEY: &str = "content_flags";
+
+/// Cookie set once a visitor passes an age gate; holds the confirmed age
+pub const AGE_GATE_COOKIE: &str = "rp_age_verified";
+
+/// Revisions kept in the history
+const MAX_HISTORY: usize = 50;
+
+/// Cookie consent banner shown to a visitor
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CookieBannerVariant {
+    /// No banner
+    None,
+    /// Informational notice only
+    #[default]
+    Notice,
+    /// Tracking on by default, with a way to refuse
+    OptOut,
+    /// Tracking off until the visitor consents
+    OptIn,
+}
+
+/// What a rule does when it matches
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ComplianceAction {
+    /// Show a cookie banner variant
+    CookieBanner { variant: CookieBannerVariant },
+    /// Require visitors to confirm their age
+    AgeGate { min_age: u8 },
+    /// Withhold the content (451 Unavailable For Legal Reasons)
+    Block {
+        #[serde(default)]
+        message: Option<String>,
+    },
+}
+
+/// Content a rule applies to; empty matches everything
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ContentSelector {
+    /// Surrogate keys, e.g. `post:{id}` or `term:category:{id}`
+    pub keys: Vec<String>,
+    /// Content flags from post meta
+    pub flags: Vec<String>,
+}
+
+impl ContentSelector {
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty() && self.flags.is_empty()
+    }
+
+    fn matches(&self, content: Option<&ContentDescriptor>) -> bool {
+        if self.is_empty() {
+            return true;
+        }
+        let Some(content) = content else {
+            return false;
+        };
+        self.keys.iter().any(|k| content.keys.contains(k))
+            || self.flags.iter().any(|f| content.flags.contains(f))
+    }
+}
+
+/// A single compliance rule
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ComplianceRule {
+    /// Stable identifier, reported in decisions and logs
+    pub id: String,
+    pub name: String,
+    /// Legal basis or rationale for the rule
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Regions the rule applies in
+    pub regions: Vec<String>,
+    /// Regions excluded even when matched by `regions`
+    #[serde(default)]
+    pub exclude_regions: Vec<String>,
+    #[serde(default)]
+    pub content: ContentSelector,
+    pub action: ComplianceAction,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl ComplianceRule {
+    fn matches_region(&self, region: &RegionContext) -> bool {
+        self.regions.iter().any(|r| region.matches(r))
+            && !self.exclude_regions.iter().any(|r| region.matches(r))
+    }
+}
+
+/// Compliance rule set
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ComplianceConfig {
+    pub enabled: bool,
+    /// Banner shown where no cookie banner rule matches
+    pub default_cookie_banner: CookieBannerVariant,
+    /// Rules in evaluation order; the first matching banner or block wins
+    pub rules: Vec<ComplianceRule>,
+    /// Incremented on every save
+    pub revision: u32,
+    pub updated_by: Option<Uuid>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl Default for ComplianceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            default_cookie_banner: CookieBannerVariant::Notice,
+            rules: vec![
+                ComplianceRule {
+                    id: "gdpr-cookie-consent".to_string(),
+                    name: "Opt-in cookie consent".to_string(),
+                    description: Some("GDPR / ePrivacy Directive, UK GDPR, Swiss FADP".to_string()),
+                    enabled: true,
+                    regions: ["EU", "IS", "LI", "NO", "GB", "CH"]
+                        .iter()
+                        .map(|r| r.to_string())
+                        .collect(),
+                    exclude_regions: Vec::new(),
+                    content: ContentSelector::default(),
+                    action: ComplianceAction::CookieBanner {
+                        variant: CookieBannerVariant::OptIn,
+                    },
+                },
+                ComplianceRule {
+                    id: "ccpa-opt-out".to_string(),
+                    name: "Opt-out cookie notice".to_string(),
+                    description: Some("CCPA / CPRA".to_string()),
+                    enabled: true,
+                    regions: vec!["US/California".to_string()],
+                    exclude_regions: Vec::new(),
+                    content: ContentSelector::default(),
+                    action: ComplianceAction::CookieBanner {
+                        variant: CookieBannerVariant::OptOut,
+                    },
+                },
+            ],
+            revision: 0,
+            updated_by: None,
+            updated_at: None,
+        }
+    }
+}
+
+impl ComplianceConfig {
+    /// Load the configuration from settings, falling back to defaults
+    pub async fn load(pool: &PgPool) -> Result<Self> {
+        COMPLIANCE_SETTING.load_or_default(pool).await
+    }
+
+    /// Persist the configuration to settings
+    pub async fn save(&self, pool: &PgPool) -> Result<()> {
+        COMPLIANCE_SETTING.save(pool, self).await
+    }
+
+    /// Check rule ids, regions and actions
+    pub fn validate(&self) -> Result<()> {
+        let mut ids = HashSet::new();
+        for rule in &self.rules {
+            if rule.id.trim().is_empty() {
+                return Err(Error::invalid_input("rules", "Rule id must not be empty"));
+            }
+            if !ids.insert(rule.id.as_str()) {
+                return Err(Error::invalid_input(
+                    "rules",
+                    format!("Duplicate rule id '{}'", rule.id),
+                ));
+            }
+            if rule.regions.is_empty() {
+                return Err(Error::invalid_input(
+                    "rules",
+                    format!("Rule '{}' has no regions", rule.id),
+                ));
+            }
+            if let Some(region) = rule
+                .regions
+                .iter()
+                .chain(&rule.exclude_regions)
+                .find(|r| !is_valid_region(r))
+            {
+                return Err(Error::invalid_input(
+                    "rules",
+                    format!("Rule '{}' has an invalid region '{}'", rule.id, region),
+                ));
+            }
+            if let ComplianceAction::AgeGate { min_age } = rule.action {
+                if !(1..=99).contains(&min_age) {
+                    return Err(Error::invalid_input(
+                        "rules",
+                        format!("Rule '{}' must have a minimum age of 1-99", rule.id),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn is_valid_region(region: &str) -> bool {
+    let is_country = |c: &str| c.len() == 2 && c.chars().all(|ch| ch.is_ascii_uppercase());
+    match region {
+        "*" | "EU" | "unknown" => true,
+        _ => match region.split_once('/') {
+            Some((country, subdivision)) => is_country(country) && !subdivision.trim().is_empty(),
+            None => is_country(region),
+        },
+    }
+}
+
+/// Where a visitor is, as far as the rules are concerned
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RegionContext {
+    /// ISO 3166-1 alpha-2 country code
+    pub country_code: Option<String>,
+    /// Subdivision name, e.g. `California`
+    pub region: Option<String>,
+    pub in_eu: bool,
+}
+
+impl RegionContext {
+    pub fn from_location(location: Option<&GeoLocation>) -> Self {
+        location
+            .map(|l| Self {
+                country_code: l.country_code.clone(),
+                region: l.region.clone(),
+                in_eu: l.in_eu,
+            })
+            .unwrap_or_default()
+    }
+
+    fn matches(&self, selector: &str) -> bool {
+        let country = self.country_code.as_deref();
+        match selector {
+            "*" => true,
+            "EU" => self.in_eu,
+            "unknown" => country.is_none(),
+            _ => match selector.split_once('/') {
+                Some((c, subdivision)) => {
+                    country.is_some_and(|code| code.eq_ignore_ascii_case(c))
+                        && self
+                            .region
+                            .as_deref()
+                            .is_some_and(|r| r.eq_ignore_ascii_case(subdivision))
+                }
+                None => country.is_some_and(|code| code.eq_ignore_ascii_case(selector)),
+            },
+        }
+    }
+}
+
+/// What a rendered page shows, used to match content rules
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ContentDescriptor {
+    /// Surrogate keys of the page
+    pub keys: Vec<String>,
+    /// Content flags of the post shown
+    pub flags: Vec<String>,
+}
+
+impl ContentDescriptor {
+    /// Read content flags from post meta (a JSON array or comma-separated text)
+    pub fn flags_from_meta(meta: &HashMap<String, Value>) -> Vec<String> {
+        match meta.get(CONTENT_FLAGS_META_KEY) {
+            Some(Value::Array(items)) => items
+                .iter()
+                .filter_map(|v| v.as_str())
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect(),
+            Some(Value::String(text)) => text
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Content withheld by a rule
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BlockDecision {
+    pub rule_id: String,
+    pub message: Option<String>,
+}
+
+/// Outcome of evaluating the rules for one request
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ComplianceDecision {
+    pub cookie_banner: CookieBannerVariant,
+    /// Minimum age visitors must confirm, if any
+    pub age_gate: Option<u8>,
+    pub blocked: Option<BlockDecision>,
+    /// Ids of the rules that took effect
+    pub matched_rules: Vec<String>,
+}
+
+/// How a single rule was evaluated
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RuleTrace {
+    pub rule_id: String,
+    pub enabled: bool,
+    pub region_matched: bool,
+    pub content_matched: bool,
+    /// Whether the rule changed the decision
+    pub applied: bool,
+}
+
+/// Evaluates a rule set
+pub struct ComplianceEngine {
+    config: ComplianceConfig,
+}
+
+impl ComplianceEngine {
+    pub fn new(config: ComplianceConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn config(&self) -> &ComplianceConfig {
+        &self.config
+    }
+
+    /// Decide what applies to a visitor viewing some content
+    pub fn evaluate(
+        &self,
+        region: &RegionContext,
+        content: Option<&ContentDescriptor>,
+    ) -> ComplianceDecision {
+        self.explain(region, content).0
+    }
+
+    /// Evaluate and report how each rule was matched
+    pub fn explain(
+        &self,
+        region: &RegionContext,
+        content: Option<&ContentDescriptor>,
+    ) -> (ComplianceDecision, Vec<RuleTrace>) {
+        let mut decision = ComplianceDecision {
+            cookie_banner: self.config.default_cookie_banner,
+            age_gate: None,
+            blocked: None,
+            matched_rules: Vec::new(),
+        };
+        let mut banner_set = false;
+        let mut trace = Vec::with_capacity(self.config.rules.len());
+
+        for rule in &self.config.rules {
+            let region_matched = rule.matches_region(region);
+            let content_matched = rule.content.matches(content);
+            let mut applied = false;
+
+            if self.config.enabled && rule.enabled && region_matched && content_matched {
+                applied = match &rule.action {
+                    ComplianceAction::CookieBanner { variant } if !banner_set => {
+                        decision.cookie_banner = *variant;
+                        banner_set = true;
+                        true
+                    }
+                    ComplianceAction::AgeGate { min_age } => {
+                        decision.age_gate = Some(decision.age_gate.unwrap_or(0).max(*min_age));
+                        true
+                    }
+                    ComplianceAction::Block { message } if decision.blocked.is_none() => {
+                        decision.blocked = Some(BlockDecision {
+                            rule_id: rule.id.clone(),
+                            message: message.clone(),
+                        });
+                        true
+                    }
+                    _ => false,
+                };
+                if applied {
+                    decision.matched_rules.push(rule.id.clone());
+                }
+            }
+
+            trace.push(RuleTrace {
+                rule_id: rule.id.clone(),
+                enabled: rule.enabled,
+                region_matched,
+                content_matched,
+                applied,
+            });
+        }
+
+        (decision, trace)
+    }
+
+    /// Whether the content is subject to region-dependent rules, so a
+    /// response showing it must not be shared between visitors
+    pub fn is_region_dependent(&self, content: &ContentDescriptor) -> bool {
+        self.config.enabled
+            && self.config.rules.iter().any(|rule| {
+                rule.enabled
+                    && !rule.content.is_empty()
+                    && !matches!(rule.action, ComplianceAction::CookieBanner { .. })
+                    && rule.content.matches(Some(content))
+            })
+    }
+}
+
+/// A saved revision of the rule set
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ComplianceRevision {
+    pub revision: u32,
+    pub updated_by: Option<Uuid>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub config: ComplianceConfig,
+}
+
+/// Loads, caches and audits the compliance rule set
+pub struct ComplianceService {
+    pool: PgPool,
+    geoip: Arc<GeoIpService>,
+    engine: RwLock<Option<Arc<ComplianceEngine>>>,
+}
+
+impl ComplianceService {
+    pub fn new(pool: PgPool, geoip: Arc<GeoIpService>) -> Self {
+        Self {
+            pool,
+            geoip,
+            engine: RwLock::new(None),
+        }
+    }
+
+    /// Current rule set, loaded from settings on first use
+    pub async fn engine(&self) -> Arc<ComplianceEngine> {
+        if let Some(engine) = self.engine.read().await.clone() {
+            return engine;
+        }
+
+        let config = ComplianceConfig::load(&self.pool)
+            .await
+            .unwrap_or_else(|e| {
+                tracing::warn!("Failed to load compliance rules, using defaults: {}", e);
+                ComplianceConfig::default()
+            });
+        let engine = Arc::new(ComplianceEngine::new(config));
+        *self.engine.write().await = Some(engine.clone());
+        engine
+    }
+
+    /// Validate, version and persist a new rule set, keeping the previous
+    /// one in the history
+    pub async fn update(
+        &self,
+        mut config: ComplianceConfig,
+        updated_by: Uuid,
+    ) -> Result<ComplianceConfig> {
+        config.validate()?;
+
+        // Read the stored revision under a lock so concurrent updates
+        // cannot both claim the same revision or drop a history entry
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| Error::database_with_source("Failed to begin transaction", e))?;
+        COMPLIANCE_SETTING.lock(&mut *tx).await?;
+
+        let previous = COMPLIANCE_SETTING.load_or_default(&mut *tx).await?;
+        config.revision = previous.revision + 1;
+        config.updated_by = Some(updated_by);
+        config.updated_at = Some(Utc::now());
+
+        let mut history = COMPLIANCE_HISTORY.load_or_default(&mut *tx).await?;
+        if previous.updated_at.is_some() {
+            history.insert(
+                0,
+                ComplianceRevision {
+                    revision: previous.revision,
+                    updated_by: previous.updated_by,
+                    updated_at: previous.updated_at,
+                    config: previous,
+                },
+            );
+            history.truncate(MAX_HISTORY);
+        }
+
+        COMPLIANCE_SETTING.save(&mut *tx, &config).await?;
+        COMPLIANCE_HISTORY.save(&mut *tx, &history).await?;
+        tx.commit()
+            .await
+            .map_err(|e| Error::database_with_source("Failed to save compliance rules", e))?;
+
+        tracing::info!(
+            revision = config.revision,
+            user_id = %updated_by,
+            rules = config.rules.len(),
+            enabled = config.enabled,
+            "Compliance rules updated"
+        );
+
+        *self.engine.write().await = Some(Arc::new(ComplianceEngine::new(config.clone())));
+        Ok(config)
+    }
+
+    /// Drop the cached rule set; the next use reloads it
+    pub async fn invalidate(&self) {
+        *self.engine.write().await = None;
+    }
+
+    /// Previous revisions, newest first
+    pub async fn history(&self) -> Result<Vec<ComplianceRevision>> {
+        COMPLIANCE_HISTORY.load_or_default(&self.pool).await
+    }
+
+    /// Region of a client IP; unresolved when GeoIP is unavailable
+    pub fn region_for(&self, ip: Option<IpAddr>) -> RegionContext {
+        let location = ip.and_then(|ip| self.geoip.lookup(ip));
+        RegionContext::from_location(location.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn region(country: &str, subdivision: Option<&str>, in_eu: bool) -> RegionContext {
+        RegionContext {
+            country_code: Some(country.to_string()),
+            region: subdivision.map(str::to_string),
+            in_eu,
+        }
+    }
+
+    fn rule(
+        id: &str,
+        regions: &[&str],
+        content: ContentSelector,
+        action: ComplianceAction,
+    ) -> ComplianceRule {
+        ComplianceRule {
+            id: id.to_string(),
+            name: id.to_string(),
+            description: None,
+            enabled: true,
+            regions: regions.iter().map(|r| r.to_string()).collect(),
+            exclude_regions: Vec::new(),
+            content,
+            action,
+        }
+    }
+
+    #[test]
+    fn test_default_cookie_banners() {
+        let engine = ComplianceEngine::new(ComplianceConfig {
+            enabled: true,
+            ..Default::default()
+        });
+
+        let de = engine.evaluate(&region("DE", None, true), None);
+        assert_eq!(de.cookie_banner, CookieBannerVariant::OptIn);
+        assert_eq!(de.matched_rules, vec!["gdpr-cookie-consent".to_string()]);
+
+        let ca = engine.evaluate(&region("US", Some("California"), false), None);
+        assert_eq!(ca.cookie_banner, CookieBannerVariant::OptOut);
+
+        let tx = engine.evaluate(&region("US", Some("Texas"), false), None);
+        assert_eq!(tx.cookie_banner, CookieBannerVariant::Notice);
+        assert!(tx.matched_rules.is_empty());
+    }
+
+    #[test]
+    fn test_age_gate_and_blocking() {
+        let adult = ContentSelector {
+            keys: Vec::new(),
+            flags: vec!["adult".to_string()],
+        };
+        let post = ContentSelector {
+            keys: vec!["post:42".to_string()],
+            flags: Vec::new(),
+        };
+        let engine = ComplianceEngine::new(ComplianceConfig {
+            enabled: true,
+            default_cookie_banner: CookieBannerVariant::None,
+            rules: vec![
+                rule(
+                    "age-18",
+                    &["*"],
+                    adult.clone(),
+                    ComplianceAction::AgeGate { min_age: 18 },
+                ),
+                rule(
+                    "age-21",
+                    &["US"],
+                    adult,
+                    ComplianceAction::AgeGate { min_age: 21 },
+                ),
+                rule(
+                    "withhold-42",
+                    &["DE", "unknown"],
+                    post,
+                    ComplianceAction::Block { message: None },
+                ),
+            ],
+            ..Default::default()
+        });
+
+        let flagged = ContentDescriptor {
+            keys: vec!["post:42".to_string()],
+            flags: vec!["adult".to_string()],
+        };
+        let plain = ContentDescriptor::default();
+
+        let us = engine.evaluate(&region("US", None, false), Some(&flagged));
+        assert_eq!(us.age_gate, Some(21));
+        assert!(us.blocked.is_none());
+
+        let de = engine.evaluate(&region("DE", None, true), Some(&flagged));
+        assert_eq!(de.age_gate, Some(18));
+        assert_eq!(de.blocked.unwrap().rule_id, "withhold-42");
+
+        let unknown = engine.evaluate(&RegionContext::default(), Some(&flagged));
+        assert!(unknown.blocked.is_some());
+
+        let (decision, trace) = engine.explain(&region("DE", None, true), Some(&plain));
+        assert!(decision.age_gate.is_none() && decision.blocked.is_none());
+        assert!(trace.iter().all(|t| !t.content_matched && !t.applied));
+
+        assert!(engine.is_region_dependent(&flagged));
+        assert!(!engine.is_region_dependent(&plain));
+    }
+
+    #[test]
+    fn test_validation() {
+        assert!(ComplianceConfig::default().validate().is_ok());
+
+        let mut config = ComplianceConfig::default();
+        config.rules[1].id = config.rules[0].id.clone();
+        assert!(config.validate().is_err());
+
+        let mut config = ComplianceConfig::default();
+        config.rules[0].regions = vec!["Europe".to_string()];
+        assert!(config.validate().is_err());
+
+        let mut config = ComplianceConfig::default();
+        config.rules[0].action = ComplianceAction::AgeGate { min_age: 0 };
+        assert!(config.validate().is_err());
+
+        let meta = HashMap::from([(
+            CONTENT_FLAGS_META_KEY.to_string(),
+            Value::String("adult, gambling".to_string()),
+        )]);
+        assert_eq!(
+            ContentDescriptor::flags_from_meta(&meta),
+            vec!["adult".to_string(), "gambling".to_string()]
+        );
+    }
+}
@@ -0,0 +1,598 @@
+//! Content Filters
+//!
+//! Post-processing applied to post and page content when it is rendered,
+//! run through the `filter_the_content` hook so plugins can add their own
+//! filters around the built-in ones:
+//!
+//! - shortlinks: `/?p=123` links in content point at the canonical URL
+//! - typography: smart quotes, dashes, ellipses and symbols
+//! - emoji: emoji characters become images, for platforms without the glyphs
+//!
+//! Filters run from the highest priority down. The built-ins sit above
+//! [`Priority::NORMAL`], so a plugin filter registered with the default
+//! priority sees their output; a higher priority runs before them. Each
+//! built-in can be switched off in the settings without unregistering it.
+//!
+//! Posts also get a numeric `short_id`; `/?p=<short_id>` redirects to the
+//! post and rendered pages advertise it with `<link rel="shortlink">`.
+
+use regex::Regex;
+use rustpress_core::error::{Error, Result};
+use rustpress_core::hook::{hooks, HookRegistry, Priority};
+use rustpress_core::tenant::site_filter;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock};
+use tokio::sync::RwLock;
+
+use super::json_setting::JsonSetting;
+use super::redirects::content_path;
+use super::regions::inject_head_tags;
+
+/// Settings key holding the content filter configuration
+pub const CONTENT_FILTERS_SETTINGS_KEY: &str = "content_filters";
+
+/// Stored content filter configuration
+const CONTENT_FILTERS_SETTING: JsonSetting<ContentFiltersConfig> = JsonSetting::new(
+    CONTENT_FILTERS_SETTINGS_KEY,
+    "reading",
+    "content filter settings",
+);
+
+/// Priority of the shortlink rewrite filter
+pub const SHORTLINK_PRIORITY: Priority = Priority(30);
+
+/// Priority of the typography filter
+pub const TYPOGRAPHY_PRIORITY: Priority = Priority(20);
+
+/// Priority of the emoji filter
+pub const EMOJI_PRIORITY: Priority = Priority(10);
+
+/// Default emoji image set (Twemoji SVGs)
+pub const DEFAULT_EMOJI_BASE_URL: &str =
+    "https://cdn.jsdelivr.net/gh/jdecked/twemoji@15.1.0/assets/svg/";
+
+/// Elements whose text is left alone
+const RAW_TEXT_ELEMENTS: [&str; 10] = [
+    "pre", "code", "kbd", "samp", "tt", "script", "style", "textarea", "svg", "math",
+];
+
+static MARKUP_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?s)<!--.*?-->|<[^>]*>").unwrap());
+
+static TAG_NAME_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^<(/?)([a-zA-Z][a-zA-Z0-9-]*)").unwrap());
+
+static SHORTLINK_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"href=(["'])((?:https?://[^/"'?#]+)?)/\?p=(\d+)["']"#).unwrap());
+
+/// Content filter configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ContentFiltersConfig {
+    /// Smart quotes, dashes, ellipses and symbols
+    pub typography: bool,
+    /// Replace emoji characters with images
+    pub emoji: bool,
+    /// Image set emoji are loaded from; file names are code points
+    /// (`1f600.svg`)
+    pub emoji_base_url: String,
+    /// Shortlink redirects, `rel="shortlink"` and rewriting `/?p=` links
+    pub shortlinks: bool,
+}
+
+impl Default for ContentFiltersConfig {
+    fn default() -> Self {
+        Self {
+            typography: true,
+            emoji: true,
+            emoji_base_url: DEFAULT_EMOJI_BASE_URL.to_string(),
+            shortlinks: true,
+        }
+    }
+}
+
+impl ContentFiltersConfig {
+    /// Load the configuration from settings, falling back to defaults
+    pub async fn load(pool: &PgPool) -> Result<Self> {
+        CONTENT_FILTERS_SETTING.load_or_default(pool).await
+    }
+
+    /// Persist the configuration to settings
+    pub async fn save(&self, pool: &PgPool) -> Result<()> {
+        CONTENT_FILTERS_SETTING.save(pool, self).await
+    }
+
+    /// Validate the emoji image URL
+    pub fn validate(&self) -> Result<()> {
+        let url = self.emoji_base_url.trim();
+        if !(url.starts_with("https://") || url.starts_with("http://") || url.starts_with('/')) {
+            return Err(Error::invalid_input(
+                "emoji_base_url",
+                "Emoji image URL must be absolute or start with '/'",
+            ));
+        }
+        if url.contains(['"', '<', '>', ' ']) {
+            return Err(Error::invalid_input(
+                "emoji_base_url",
+                "Emoji image URL contains invalid characters",
+            ));
+        }
+        Ok(())
+    }
+
+    fn emoji_base(&self) -> String {
+        let base = self.emoji_base_url.trim();
+        if base.ends_with('/') {
+            base.to_string()
+        } else {
+            format!("{}/", base)
+        }
+    }
+}
+
+/// State the built-in filters read when they run
+struct FilterState {
+    pool: PgPool,
+    config: parking_lot::RwLock<Option<Arc<ContentFiltersConfig>>>,
+}
+
+impl FilterState {
+    fn config(&self) -> Arc<ContentFiltersConfig> {
+        self.config.read().clone().unwrap_or_default()
+    }
+}
+
+/// Runs the content filter chain
+pub struct ContentFilterService {
+    hooks: Arc<RwLock<HookRegistry>>,
+    state: Arc<FilterState>,
+}
+
+impl ContentFilterService {
+    /// Create the service and register the built-in filters on `hooks`.
+    /// Call before the registry is shared, while nothing else holds it.
+    pub fn new(pool: PgPool, hooks: Arc<RwLock<HookRegistry>>) -> Self {
+        let state = Arc::new(FilterState {
+            pool,
+            config: parking_lot::RwLock::new(None),
+        });
+        match hooks.try_read() {
+            Ok(registry) => register_builtin_filters(&registry, &state),
+            Err(_) => {
+                tracing::warn!("Hook registry is locked, built-in content filters not registered")
+            }
+        }
+        Self { hooks, state }
+    }
+
+    /// Content filter configuration, loaded from settings on first use
+    pub async fn config(&self) -> Arc<ContentFiltersConfig> {
+        if let Some(config) = self.state.config.read().clone() {
+            return config;
+        }
+
+        match ContentFiltersConfig::load(&self.state.pool).await {
+            Ok(config) => {
+                let config = Arc::new(config);
+                *self.state.config.write() = Some(config.clone());
+                config
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "Failed to load content filter settings, using defaults: {}",
+                    e
+                );
+                Arc::new(ContentFiltersConfig::default())
+            }
+        }
+    }
+
+    /// Replace the cached configuration after it was saved
+    pub fn set_config(&self, config: ContentFiltersConfig) {
+        *self.state.config.write() = Some(Arc::new(config));
+    }
+
+    /// Drop the cached configuration; the next `apply` reloads it
+    pub fn invalidate(&self) {
+        *self.state.config.write() = None;
+    }
+
+    /// Run rendered content through the filter chain
+    pub async fn apply(&self, content: String) -> String {
+        // Built-in filters read the cached configuration
+        self.config().await;
+        self.hooks
+            .read()
+            .await
+            .apply_typed_filter(&hooks::typed::THE_CONTENT, content)
+            .await
+    }
+
+    /// Canonical path of the published post or page with `short_id`
+    pub async fn resolve_shortlink(&self, short_id: i64) -> Result<Option<String>> {
+        if !self.config().await.shortlinks {
+            return Ok(None);
+        }
+        Ok(canonical_paths(&self.state.pool, &[short_id])
+            .await?
+            .remove(&short_id))
+    }
+
+    /// Shortlink URL for a post, when shortlinks are enabled
+    pub async fn shortlink(&self, site_url: &str, short_id: Option<i64>) -> Option<String> {
+        let short_id = short_id?;
+        self.config()
+            .await
+            .shortlinks
+            .then(|| format!("{}/?p={}", site_url.trim_end_matches('/'), short_id))
+    }
+}
+
+fn register_builtin_filters(registry: &HookRegistry, state: &Arc<FilterState>) {
+    let shared = state.clone();
+    registry.add_typed_filter(
+        &hooks::typed::THE_CONTENT,
+        move |content: String| {
+            let state = shared.clone();
+            async move {
+                if !state.config().shortlinks {
+                    return content;
+                }
+                match rewrite_shortlinks(&state.pool, &content).await {
+                    Ok(rewritten) => rewritten,
+                    Err(e) => {
+                        tracing::warn!("Failed to resolve shortlinks in content: {}", e);
+                        content
+                    }
+                }
+            }
+        },
+        SHORTLINK_PRIORITY,
+        None,
+    );
+
+    let shared = state.clone();
+    registry.add_typed_filter(
+        &hooks::typed::THE_CONTENT,
+        move |content: String| {
+            let enabled = shared.config().typography;
+            async move {
+                if enabled {
+                    texturize(&content)
+                } else {
+                    content
+                }
+            }
+        },
+        TYPOGRAPHY_PRIORITY,
+        None,
+    );
+
+    let shared = state.clone();
+    registry.add_typed_filter(
+        &hooks::typed::THE_CONTENT,
+        move |content: String| {
+            let config = shared.config();
+            async move {
+                if config.emoji {
+                    replace_emoji(&content, &config.emoji_base())
+                } else {
+                    content
+                }
+            }
+        },
+        EMOJI_PRIORITY,
+        None,
+    );
+}
+
+/// Canonical paths of published posts and pages by short id
+async fn canonical_paths(pool: &PgPool, short_ids: &[i64]) -> Result<HashMap<i64, String>> {
+    let rows: Vec<(i64, String, String)> = sqlx::query_as(&format!(
+        r#"
+        SELECT short_id, slug, post_type::text FROM posts
+        WHERE short_id = ANY($1) AND status = 'published' AND deleted_at IS NULL
+          AND post_type IN ('post', 'page', 'episode', 'event') AND {}
+        "#,
+        site_filter("site_id")
+    ))
+    .bind(short_ids)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| Error::database_with_source("Failed to resolve shortlinks", e))?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(id, slug, post_type)| (id, content_path(&post_type, &slug)))
+        .collect())
+}
+
+/// Point `/?p=123` links at canonical URLs. Absolute links keep their host,
+/// which only matters for links to this site; unknown ids are left alone.
+async fn rewrite_shortlinks(pool: &PgPool, content: &str) -> Result<String> {
+    let ids: Vec<i64> = SHORTLINK_RE
+        .captures_iter(content)
+        .filter_map(|caps| caps[3].parse().ok())
+        .collect();
+    if ids.is_empty() {
+        return Ok(content.to_string());
+    }
+
+    let paths = canonical_paths(pool, &ids).await?;
+    Ok(SHORTLINK_RE
+        .replace_all(content, |caps: &regex::Captures| {
+            match caps[3].parse::<i64>().ok().and_then(|id| paths.get(&id)) {
+                Some(path) => format!("href={}{}{}{}", &caps[1], &caps[2], path, &caps[1]),
+                None => caps[0].to_string(),
+            }
+        })
+        .into_owned())
+}
+
+/// Insert `<link rel="shortlink">` before `</head>`
+pub fn inject_shortlink(html: &str, shortlink: &str) -> String {
+    let tag = format!(
+        "<link rel=\"shortlink\" href=\"{}\">",
+        shortlink.replace('&', "&amp;").replace('"', "&quot;")
+    );
+    inject_head_tags(html, &tag)
+}
+
+/// Rewrite the text between tags, skipping raw-text elements such as
+/// `<code>`. `f` also learns whether the text follows an opening tag.
+fn map_text(html: &str, mut f: impl FnMut(&str, bool) -> String) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut raw_depth = 0usize;
+    let mut after_open_tag = true;
+    let mut last = 0;
+
+    let mut emit_text = |out: &mut String, text: &str, raw_depth: usize, after_open: bool| {
+        if raw_depth == 0 && !text.is_empty() {
+            out.push_str(&f(text, after_open));
+        } else {
+            out.push_str(text);
+        }
+    };
+
+    for markup in MARKUP_RE.find_iter(html) {
+        let text = &html[last..markup.start()];
+        emit_text(&mut out, text, raw_depth, after_open_tag);
+        out.push_str(markup.as_str());
+        last = markup.end();
+
+        if let Some(caps) = TAG_NAME_RE.captures(markup.as_str()) {
+            let closing = !caps[1].is_empty();
+            let name = caps[2].to_ascii_lowercase();
+            if RAW_TEXT_ELEMENTS.contains(&name.as_str()) && !markup.as_str().ends_with("/>") {
+                if closing {
+                    raw_depth = raw_depth.saturating_sub(1);
+                } else {
+                    raw_depth += 1;
+                }
+            }
+            after_open_tag = !closing;
+        }
+    }
+    emit_text(&mut out, &html[last..], raw_depth, after_open_tag);
+    out
+}
+
+/// Smart quotes, dashes, ellipses and symbols
+pub fn texturize(html: &str) -> String {
+    let mut prev: Option<char> = None;
+    map_text(html, |text, after_open_tag| {
+        if after_open_tag {
+            prev = None;
+        }
+        let text = text
+            .replace("---", "\u{2014}")
+            .replace(" -- ", " \u{2013} ")
+            .replace("...", "\u{2026}")
+            .replace("(c)", "\u{a9}")
+            .replace("(r)", "\u{ae}")
+            .replace("(tm)", "\u{2122}");
+
+        let mut out = String::with_capacity(text.len());
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            let opening = prev.is_none_or(|p| {
+                p.is_whitespace() || "([{\u{2014}\u{2013}-/\u{201c}\u{2018}".contains(p)
+            });
+            let next = chars.peek().copied();
+            let replaced = match c {
+                '"' if opening => '\u{201c}',
+                '"' => '\u{201d}',
+                // '90s and similar elisions
+                '\'' if opening && next.is_some_and(|n| n.is_ascii_digit()) => '\u{2019}',
+                '\'' if opening => '\u{2018}',
+                '\'' => '\u{2019}',
+                other => other,
+            };
+            out.push(replaced);
+            prev = Some(replaced);
+        }
+        out
+    })
+}
+
+/// Pictographs that always render as emoji
+fn is_emoji_base(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x1F000..=0x1F02F
+            | 0x1F0A0..=0x1F0FF
+            | 0x1F170..=0x1F19A
+            | 0x1F201..=0x1F251
+            | 0x1F300..=0x1F3FA
+            | 0x1F400..=0x1F64F
+            | 0x1F680..=0x1F6FF
+            | 0x1F7E0..=0x1F7EB
+            | 0x1F90C..=0x1F9FF
+            | 0x1FA70..=0x1FAFF
+            | 0x231A..=0x231B
+            | 0x23E9..=0x23EC
+            | 0x23F0
+            | 0x23F3
+            | 0x2B50
+            | 0x2B55
+    )
+}
+
+/// Symbols that are text by default and emoji with U+FE0F
+fn is_emoji_symbol(c: char) -> bool {
+    matches!(c as u32, 0x2600..=0x27BF | 0x2190..=0x21FF | 0x2934..=0x2935 | 0x3030 | 0x303D)
+}
+
+fn is_regional_indicator(c: char) -> bool {
+    matches!(c as u32, 0x1F1E6..=0x1F1FF)
+}
+
+fn is_skin_tone(c: char) -> bool {
+    matches!(c as u32, 0x1F3FB..=0x1F3FF)
+}
+
+const VARIATION_SELECTOR: char = '\u{FE0F}';
+const ZERO_WIDTH_JOINER: char = '\u{200D}';
+
+/// Length in chars of the emoji sequence starting at `chars[0]`, if any
+fn emoji_sequence_len(chars: &[char]) -> Option<usize> {
+    let first = *chars.first()?;
+
+    // Flags are pairs of regional indicators
+    if is_regional_indicator(first) {
+        return chars
+            .get(1)
+            .filter(|c| is_regional_indicator(**c))
+            .map(|_| 2);
+    }
+
+    let element = |at: usize| -> Option<usize> {
+        let c = *chars.get(at)?;
+        let mut len = if is_emoji_base(c) {
+            1
+        } else if is_emoji_symbol(c) && chars.get(at + 1) == Some(&VARIATION_SELECTOR) {
+            2
+        } else {
+            return None;
+        };
+        if chars.get(at + len).copied().is_some_and(is_skin_tone) {
+            len += 1;
+        }
+        if chars.get(at + len) == Some(&VARIATION_SELECTOR) {
+            len += 1;
+        }
+        Some(len)
+    };
+
+    let mut len = element(0)?;
+    while chars.get(len) == Some(&ZERO_WIDTH_JOINER) {
+        match element(len + 1) {
+            Some(next) => len += 1 + next,
+            None => break,
+        }
+    }
+    Some(len)
+}
+
+/// Image file name of an emoji sequence (Twemoji naming)
+fn emoji_file_name(sequence: &[char]) -> String {
+    let joined = sequence.contains(&ZERO_WIDTH_JOINER);
+    sequence
+        .iter()
+        .filter(|c| joined || **c != VARIATION_SELECTOR)
+        .map(|c| format!("{:x}", *c as u32))
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Replace emoji characters with images from `base_url`
+pub fn replace_emoji(html: &str, base_url: &str) -> String {
+    map_text(html, |text, _| {
+        let chars: Vec<char> = text.chars().collect();
+        let mut out = String::with_capacity(text.len());
+        let mut i = 0;
+        while i < chars.len() {
+            match emoji_sequence_len(&chars[i..]) {
+                Some(len) => {
+                    let sequence = &chars[i..i + len];
+                    let alt: String = sequence.iter().collect();
+                    out.push_str(&format!(
+                        "<img draggable=\"false\" role=\"img\" class=\"emoji\" alt=\"{}\" src=\"{}{}.svg\">",
+                        alt,
+                        base_url,
+                        emoji_file_name(sequence)
+                    ));
+                    i += len;
+                }
+                None => {
+                    out.push(chars[i]);
+                    i += 1;
+                }
+            }
+        }
+        out
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_texturize() {
+        assert_eq!(
+            texturize(r#"<p>"Hello," she said... it's the '90s -- (c) 2024---done</p>"#),
+            "<p>\u{201c}Hello,\u{201d} she said\u{2026} it\u{2019}s the \u{2019}90s \u{2013} \u{a9} 2024\u{2014}done</p>"
+        );
+        // Quotes after an opening tag open; after a closing tag they close
+        assert_eq!(
+            texturize(r#"<p>"<em>Quoted</em>"</p><p>'Single'</p>"#),
+            "<p>\u{201c}<em>Quoted</em>\u{201d}</p><p>\u{2018}Single\u{2019}</p>"
+        );
+        // Attributes and code are left alone
+        assert_eq!(
+            texturize(r#"<a title="x -- y">a</a> <code>"raw" -- x</code>"#),
+            r#"<a title="x -- y">a</a> <code>"raw" -- x</code>"#
+        );
+    }
+
+    #[test]
+    fn test_replace_emoji() {
+        let base = "https://emoji.example/";
+        assert_eq!(
+            replace_emoji("<p>Hi 😀</p>", base),
+            "<p>Hi <img draggable=\"false\" role=\"img\" class=\"emoji\" alt=\"😀\" src=\"https://emoji.example/1f600.svg\"></p>"
+        );
+
+        // Variation selectors are dropped from single emoji, kept in ZWJ sequences
+        let heart = replace_emoji("\u{2764}\u{FE0F}", base);
+        assert!(heart.ends_with("/2764.svg\">"));
+        let family = replace_emoji("👩\u{200D}❤\u{FE0F}\u{200D}👨", base);
+        assert!(family.ends_with("/1f469-200d-2764-fe0f-200d-1f468.svg\">"));
+        let thumbs = replace_emoji("👍🏽", base);
+        assert!(thumbs.ends_with("/1f44d-1f3fd.svg\">"));
+        let flag = replace_emoji("🇳🇱", base);
+        assert!(flag.ends_with("/1f1f3-1f1f1.svg\">"));
+
+        // Text-presentation symbols and code stay as they are
+        assert_eq!(replace_emoji("\u{2764} ok", base), "\u{2764} ok");
+        assert_eq!(replace_emoji("<code>😀</code>", base), "<code>😀</code>");
+    }
+
+    #[test]
+    fn test_shortlink_pattern_and_head_link() {
+        let content = r#"<a href="/?p=12">a</a> <a href='https://example.com/?p=7'>b</a> <a href="/?page=2">c</a>"#;
+        let ids: Vec<&str> = SHORTLINK_RE
+            .captures_iter(content)
+            .map(|caps| caps.get(3).unwrap().as_str())
+            .collect();
+        assert_eq!(ids, vec!["12", "7"]);
+
+        assert_eq!(
+            inject_shortlink("<html><head></head></html>", "https://example.com/?p=7"),
+            "<html><head><link rel=\"shortlink\" href=\"https://example.com/?p=7\">\n</head></html>"
+        );
+    }
+}
@@ -0,0 +1,465 @@
+//! Content Performance
+//!
+//! Joins published posts and pages with the analytics plugin's daily
+//! rollups (`rustanalytics_daily_data`) so editors can see which content
+//! draws readers. Pages are matched to posts by path (`/{post_type}/{slug}`,
+//! the same URLs the cache warmer uses) and rows carry the post's author,
+//! categories, publish date and word count next to its views, engagement
+//! rate and decay.
+//!
+//! Decay compares a post's average daily views over its last week with its
+//! first week after publishing: 0 means it is as busy as at launch, 0.9
+//! means it lost 90% of its launch traffic and negative values mean it is
+//! growing. Posts whose launch week predates the rollups have no decay.
+//!
+//! When the analytics plugin is not installed the rollups table is missing
+//! and every post reports zero views.
+
+use chrono::{DateTime, Utc};
+use rustpress_core::api::SortOrder;
+use rustpress_core::error::{Error, Result};
+use rustpress_core::tenant::site_filter;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use std::fmt;
+use std::str::FromStr;
+use uuid::Uuid;
+
+/// Days of rollups used when the caller does not ask for a number
+pub const DEFAULT_DAYS: u32 = 30;
+
+/// Most days of rollups one report covers
+pub const MAX_DAYS: u32 = 365;
+
+/// Rows per page when the caller does not ask for a number
+pub const DEFAULT_PER_PAGE: u32 = 20;
+
+/// Most rows per page
+pub const MAX_PER_PAGE: u32 = 100;
+
+/// Days compared at launch and now when computing decay
+const DECAY_WINDOW_DAYS: i32 = 7;
+
+/// Views per path and day from the analytics rollups
+const ROLLUP_VIEWS: &str = r#"
+    SELECT page->>'page_path' AS path,
+           d.data_date,
+           SUM(COALESCE((page->>'pageviews')::bigint, 0))::float8 AS pageviews,
+           SUM(COALESCE((page->>'entrances')::bigint, 0))::float8 AS sessions,
+           SUM(COALESCE((page->>'entrances')::bigint, 0)
+               * COALESCE((page->>'bounce_rate')::float8, 0) / 100.0) AS bounces,
+           SUM(COALESCE((page->>'pageviews')::bigint, 0)
+               * COALESCE((page->>'avg_time_on_page')::float8, 0)) AS time_on_page
+    FROM rustanalytics_daily_data d,
+         jsonb_array_elements(COALESCE(d.pages, '[]'::jsonb)) AS page
+    WHERE page ? 'page_path'
+    GROUP BY 1, 2
+"#;
+
+/// Stand-in for [`ROLLUP_VIEWS`] when the analytics plugin is not installed
+const NO_VIEWS: &str = r#"
+    SELECT NULL::text AS path, NULL::date AS data_date, 0::float8 AS pageviews,
+           0::float8 AS sessions, 0::float8 AS bounces, 0::float8 AS time_on_page
+    WHERE false
+"#;
+
+/// Whether the analytics plugin's rollups table exists
+pub(crate) async fn analytics_available(pool: &PgPool) -> Result<bool> {
+    let (available,): (bool,) =
+        sqlx::query_as("SELECT to_regclass('rustanalytics_daily_data') IS NOT NULL")
+            .fetch_one(pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to look up analytics rollups", e))?;
+    Ok(available)
+}
+
+/// Views per path and day, or no rows when the rollups are missing
+pub(crate) fn views_source(analytics_available: bool) -> &'static str {
+    if analytics_available {
+        ROLLUP_VIEWS
+    } else {
+        NO_VIEWS
+    }
+}
+
+/// Column a content performance report is ordered by
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentSort {
+    #[default]
+    Views,
+    EngagementRate,
+    Decay,
+    PublishedAt,
+    WordCount,
+}
+
+impl ContentSort {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Views => "views",
+            Self::EngagementRate => "engagement_rate",
+            Self::Decay => "decay",
+            Self::PublishedAt => "published_at",
+            Self::WordCount => "word_count",
+        }
+    }
+
+    /// Column of the report query to order by
+    fn column(&self) -> &'static str {
+        match self {
+            Self::Views => "r.pageviews",
+            Self::EngagementRate => "r.engagement_rate",
+            Self::Decay => "r.decay",
+            Self::PublishedAt => "p.published_at",
+            Self::WordCount => "word_count",
+        }
+    }
+}
+
+impl fmt::Display for ContentSort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ContentSort {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "views" => Ok(Self::Views),
+            "engagement_rate" => Ok(Self::EngagementRate),
+            "decay" => Ok(Self::Decay),
+            "published_at" => Ok(Self::PublishedAt),
+            "word_count" => Ok(Self::WordCount),
+            _ => Err(Error::invalid_input(
+                "sort",
+                format!(
+                    "Unknown sort '{}'; expected one of: views, engagement_rate, decay, published_at, word_count",
+                    s
+                ),
+            )),
+        }
+    }
+}
+
+fn default_days() -> u32 {
+    DEFAULT_DAYS
+}
+
+fn default_page() -> u32 {
+    1
+}
+
+fn default_per_page() -> u32 {
+    DEFAULT_PER_PAGE
+}
+
+/// Filters, ordering and paging of a content performance report
+#[derive(Debug, Clone, Deserialize)]
+pub struct ContentPerformanceQuery {
+    /// Days of rollups views and engagement are summed over
+    #[serde(default = "default_days")]
+    pub days: u32,
+    #[serde(default)]
+    pub sort: ContentSort,
+    #[serde(default)]
+    pub order: SortOrder,
+    /// `post` or `page`; both when unset
+    pub post_type: Option<String>,
+    pub author_id: Option<Uuid>,
+    /// Category slug
+    pub category: Option<String>,
+    #[serde(default = "default_page")]
+    pub page: u32,
+    #[serde(default = "default_per_page")]
+    pub per_page: u32,
+}
+
+impl Default for ContentPerformanceQuery {
+    fn default() -> Self {
+        Self {
+            days: DEFAULT_DAYS,
+            sort: ContentSort::default(),
+            order: SortOrder::default(),
+            post_type: None,
+            author_id: None,
+            category: None,
+            page: 1,
+            per_page: DEFAULT_PER_PAGE,
+        }
+    }
+}
+
+impl ContentPerformanceQuery {
+    pub fn validate(&self) -> Result<()> {
+        if self.days == 0 || self.days > MAX_DAYS {
+            return Err(Error::invalid_input(
+                "days",
+                format!("Days must be between 1 and {}", MAX_DAYS),
+            ));
+        }
+        if let Some(post_type) = &self.post_type {
+            if post_type != "post" && post_type != "page" {
+                return Err(Error::invalid_input(
+                    "post_type",
+                    "Post type must be 'post' or 'page'",
+                ));
+            }
+        }
+        if self.page == 0 {
+            return Err(Error::invalid_input("page", "Page must be at least 1"));
+        }
+        if self.per_page == 0 || self.per_page > MAX_PER_PAGE {
+            return Err(Error::invalid_input(
+                "per_page",
+                format!("Per page must be between 1 and {}", MAX_PER_PAGE),
+            ));
+        }
+        Ok(())
+    }
+
+    fn offset(&self) -> i64 {
+        (self.page as i64 - 1) * self.per_page as i64
+    }
+}
+
+/// Performance of one post or page
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct ContentPerformance {
+    pub post_id: Uuid,
+    pub title: String,
+    pub post_type: String,
+    pub path: String,
+    pub author_id: Option<Uuid>,
+    pub author_name: Option<String>,
+    pub categories: Vec<String>,
+    pub published_at: Option<DateTime<Utc>>,
+    pub word_count: i32,
+    /// Views over the report window
+    pub views: i64,
+    /// Sessions that entered on the post over the report window
+    pub sessions: i64,
+    /// Share of those sessions that did not bounce, 0 to 1
+    pub engagement_rate: Option<f64>,
+    /// Seconds, averaged over views
+    pub avg_time_on_page: Option<f64>,
+    /// Average daily views over the first week after publishing
+    pub launch_daily_views: Option<f64>,
+    /// Average daily views over the last week
+    pub recent_daily_views: f64,
+    /// Share of launch traffic lost since, see the module docs
+    pub decay: Option<f64>,
+}
+
+/// A page of content performance rows
+#[derive(Debug, Clone, Serialize)]
+pub struct ContentPerformanceReport {
+    pub days: u32,
+    pub sort: ContentSort,
+    /// Whether analytics rollups were found; without them every view count
+    /// is zero
+    pub analytics_available: bool,
+    pub total: u64,
+    pub items: Vec<ContentPerformance>,
+}
+
+#[derive(FromRow)]
+struct ReportRow {
+    #[sqlx(flatten)]
+    item: ContentPerformance,
+    total: i64,
+}
+
+/// Builds content performance reports
+pub struct ContentPerformanceService {
+    pool: PgPool,
+}
+
+impl ContentPerformanceService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// One page of published content ranked by `query.sort`
+    pub async fn report(
+        &self,
+        query: &ContentPerformanceQuery,
+    ) -> Result<ContentPerformanceReport> {
+        query.validate()?;
+
+        let analytics_available = analytics_available(&self.pool).await?;
+        let sql = Self::build_query(query, analytics_available);
+        let rows: Vec<ReportRow> = sqlx::query_as(&sql)
+            .bind(query.days as i32)
+            .bind(DECAY_WINDOW_DAYS)
+            .bind(query.post_type.as_deref())
+            .bind(query.author_id)
+            .bind(query.category.as_deref())
+            .bind(query.per_page as i64)
+            .bind(query.offset())
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to load content performance", e))?;
+
+        let total = rows.first().map(|row| row.total as u64).unwrap_or(0);
+        Ok(ContentPerformanceReport {
+            days: query.days,
+            sort: query.sort,
+            analytics_available,
+            total,
+            items: rows.into_iter().map(|row| row.item).collect(),
+        })
+    }
+
+    /// Report query; binds are days, decay window, post type, author,
+    /// category slug, limit and offset
+    fn build_query(query: &ContentPerformanceQuery, analytics_available: bool) -> String {
+        let views = views_source(analytics_available);
+        let order = match query.order {
+            SortOrder::Asc => "ASC NULLS LAST",
+            SortOrder::Desc => "DESC NULLS LAST",
+        };
+
+        format!(
+            r#"
+            WITH views AS ({views}),
+            content AS (
+                SELECT p.id, p.published_at,
+                       -- Days since publishing, capped at the decay window
+                       LEAST($2, GREATEST(1, CURRENT_DATE - p.published_at::date + 1)) AS decay_days
+                FROM posts p
+                WHERE p.status = 'published' AND p.deleted_at IS NULL AND {site}
+                  AND p.post_type IN ('post', 'page')
+                  AND ($3::text IS NULL OR p.post_type = $3)
+                  AND ($4::uuid IS NULL OR p.author_id = $4)
+                  AND ($5::text IS NULL OR EXISTS (
+                      SELECT 1 FROM post_categories pc
+                      JOIN categories c ON c.id = pc.category_id
+                      WHERE pc.post_id = p.id AND c.slug = $5
+                  ))
+            ),
+            stats AS (
+                SELECT c.id,
+                       COALESCE(SUM(v.pageviews) FILTER (WHERE v.data_date > CURRENT_DATE - $1), 0) AS pageviews,
+                       COALESCE(SUM(v.sessions) FILTER (WHERE v.data_date > CURRENT_DATE - $1), 0) AS sessions,
+                       SUM(v.bounces) FILTER (WHERE v.data_date > CURRENT_DATE - $1) AS bounces,
+                       SUM(v.time_on_page) FILTER (WHERE v.data_date > CURRENT_DATE - $1) AS time_on_page,
+                       SUM(v.pageviews) FILTER (
+                           WHERE v.data_date >= c.published_at::date
+                             AND v.data_date < c.published_at::date + c.decay_days
+                       ) / c.decay_days AS launch_daily_views,
+                       COALESCE(SUM(v.pageviews) FILTER (
+                           WHERE v.data_date > CURRENT_DATE - c.decay_days
+                       ), 0) / c.decay_days AS recent_daily_views
+                FROM content c
+                JOIN posts p ON p.id = c.id
+                LEFT JOIN views v ON v.path = '/' || p.post_type || '/' || p.slug
+                GROUP BY c.id, c.published_at, c.decay_days
+            ),
+            ranked AS (
+                SELECT s.*,
+                       CASE WHEN s.sessions > 0 THEN 1 - s.bounces / s.sessions END AS engagement_rate,
+                       CASE WHEN s.pageviews > 0 THEN s.time_on_page / s.pageviews END AS avg_time_on_page,
+                       CASE WHEN s.launch_daily_views > 0
+                            THEN 1 - s.recent_daily_views / s.launch_daily_views END AS decay,
+                       COUNT(*) OVER () AS total
+                FROM stats s
+            )
+            SELECT p.id AS post_id, p.title, p.post_type,
+                   '/' || p.post_type || '/' || p.slug AS path,
+                   p.author_id,
+                   COALESCE(u.display_name, u.username) AS author_name,
+                   ARRAY(
+                       SELECT c.name FROM post_categories pc
+                       JOIN categories c ON c.id = pc.category_id
+                       WHERE pc.post_id = p.id
+                       ORDER BY c.name
+                   ) AS categories,
+                   p.published_at,
+                   COALESCE(array_length(regexp_split_to_array(
+                       NULLIF(btrim(regexp_replace(COALESCE(p.content, ''), '<[^>]*>', ' ', 'g')), ''),
+                       '\s+'
+                   ), 1), 0) AS word_count,
+                   r.pageviews::bigint AS views,
+                   r.sessions::bigint AS sessions,
+                   r.engagement_rate::float8 AS engagement_rate,
+                   r.avg_time_on_page::float8 AS avg_time_on_page,
+                   r.launch_daily_views::float8 AS launch_daily_views,
+                   r.recent_daily_views::float8 AS recent_daily_views,
+                   r.decay::float8 AS decay,
+                   r.total
+            FROM ranked r
+            JOIN posts p ON p.id = r.id
+            LEFT JOIN users u ON u.id = p.author_id
+            ORDER BY {column} {order}, p.published_at DESC NULLS LAST, p.id
+            LIMIT $6 OFFSET $7
+            "#,
+            views = views,
+            site = site_filter("p.site_id"),
+            column = query.sort.column(),
+            order = order,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sort_parsing() {
+        for sort in [
+            ContentSort::Views,
+            ContentSort::EngagementRate,
+            ContentSort::Decay,
+            ContentSort::PublishedAt,
+            ContentSort::WordCount,
+        ] {
+            assert_eq!(sort.as_str().parse::<ContentSort>().unwrap(), sort);
+        }
+        assert!("popularity".parse::<ContentSort>().is_err());
+    }
+
+    #[test]
+    fn test_query_validation() {
+        assert!(ContentPerformanceQuery::default().validate().is_ok());
+
+        let too_long = ContentPerformanceQuery {
+            days: MAX_DAYS + 1,
+            ..Default::default()
+        };
+        assert!(too_long.validate().is_err());
+
+        let attachments = ContentPerformanceQuery {
+            post_type: Some("attachment".to_string()),
+            ..Default::default()
+        };
+        assert!(attachments.validate().is_err());
+
+        let huge_page = ContentPerformanceQuery {
+            per_page: MAX_PER_PAGE + 1,
+            ..Default::default()
+        };
+        assert!(huge_page.validate().is_err());
+    }
+
+    #[test]
+    fn test_build_query() {
+        let query = ContentPerformanceQuery {
+            sort: ContentSort::Decay,
+            order: SortOrder::Asc,
+            page: 3,
+            ..Default::default()
+        };
+        assert_eq!(query.offset(), 40);
+
+        let sql = ContentPerformanceService::build_query(&query, true);
+        assert!(sql.contains("FROM rustanalytics_daily_data"));
+        assert!(sql.contains("ORDER BY r.decay ASC NULLS LAST"));
+
+        let sql = ContentPerformanceService::build_query(&query, false);
+        assert!(!sql.contains("rustanalytics_daily_data"));
+    }
+}
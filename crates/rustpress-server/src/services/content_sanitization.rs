@@ -0,0 +1,655 @@
+//! Content Sanitization Policy
+//!
+//! Sanitizes user-generated HTML with the ammonia-based sanitizer from
+//! `rustpress-content` before it is stored. Each role gets its own policy,
+//! so administrators can embed iframes while contributors cannot; a user
+//! is sanitized with the policy of the first role in the policy list they
+//! hold. Comments have a policy of their own, and legacy imported HTML
+//! (classic content and raw HTML blocks) can be swept with the legacy
+//! policy.
+//!
+//! Whenever a pass strips something, a report of the removed tags,
+//! attributes and comments is kept in `content_sanitization_reports`.
+
+use chrono::{DateTime, Utc};
+use rustpress_content::sanitize::{
+    sanitize_legacy_html, SanitizationLevel, SanitizeConfig, SanitizeReport, Sanitizer,
+};
+use rustpress_core::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use sqlx::{FromRow, PgPool};
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use super::json_setting::JsonSetting;
+
+/// Settings key holding the sanitization policy
+pub const SANITIZATION_SETTINGS_KEY: &str = "content_sanitization";
+
+/// Stored sanitization policy
+const SANITIZATION_SETTING: JsonSetting<SanitizationPolicy> = JsonSetting::new(
+    SANITIZATION_SETTINGS_KEY,
+    "security",
+    "content sanitization policy",
+);
+
+/// Most posts one legacy sweep batch may scan
+pub const MAX_SWEEP_BATCH: i64 = 200;
+
+/// URL schemes a policy may never allow
+const FORBIDDEN_SCHEMES: [&str; 3] = ["javascript", "vbscript", "file"];
+
+/// Allowlist for one role or content source
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RolePolicy {
+    /// Role the policy applies to; informational for the comment, legacy
+    /// and fallback policies
+    pub role: String,
+    /// Base allowlist
+    pub level: SanitizationLevel,
+    /// Tags allowed on top of the level
+    #[serde(default)]
+    pub allow_tags: Vec<String>,
+    /// Tags removed even when the level allows them
+    #[serde(default)]
+    pub deny_tags: Vec<String>,
+    /// Extra attributes allowed per tag
+    #[serde(default)]
+    pub allow_attributes: BTreeMap<String, Vec<String>>,
+    /// Allowed URL schemes; empty keeps the sanitizer defaults
+    #[serde(default)]
+    pub url_schemes: Vec<String>,
+}
+
+impl RolePolicy {
+    fn new(role: &str, level: SanitizationLevel) -> Self {
+        Self {
+            role: role.to_string(),
+            level,
+            allow_tags: Vec::new(),
+            deny_tags: Vec::new(),
+            allow_attributes: BTreeMap::new(),
+            url_schemes: Vec::new(),
+        }
+    }
+
+    fn denying(mut self, tags: &[&str]) -> Self {
+        self.deny_tags = tags.iter().map(|t| t.to_string()).collect();
+        self
+    }
+
+    /// Sanitizer for this policy
+    pub fn sanitizer(&self) -> Sanitizer {
+        let mut config = SanitizeConfig {
+            level: self.level,
+            additional_tags: lowercase(&self.allow_tags),
+            blocked_tags: lowercase(&self.deny_tags),
+            additional_attributes: self
+                .allow_attributes
+                .iter()
+                .map(|(tag, attributes)| (tag.to_ascii_lowercase(), lowercase(attributes)))
+                .collect(),
+            // Content is stored; links are decorated when it is rendered
+            linkify_urls: false,
+            link_target_blank: false,
+            ..Default::default()
+        };
+        if !self.url_schemes.is_empty() {
+            config.url_schemes = lowercase(&self.url_schemes);
+        }
+        Sanitizer::new(config)
+    }
+
+    fn validate(&self, field: &str) -> Result<()> {
+        let names = self
+            .allow_tags
+            .iter()
+            .chain(&self.deny_tags)
+            .chain(self.allow_attributes.keys())
+            .chain(self.allow_attributes.values().flatten());
+        for name in names {
+            let valid = !name.is_empty()
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == ':');
+            if !valid {
+                return Err(Error::invalid_input(
+                    field,
+                    format!("Invalid tag or attribute name '{}'", name),
+                ));
+            }
+        }
+
+        // Event handlers would undo the whole point of the policy
+        if let Some(attribute) = self
+            .allow_attributes
+            .values()
+            .flatten()
+            .find(|a| a.to_ascii_lowercase().starts_with("on"))
+        {
+            return Err(Error::invalid_input(
+                field,
+                format!("Event handler attribute '{}' cannot be allowed", attribute),
+            ));
+        }
+        if let Some(scheme) = self
+            .url_schemes
+            .iter()
+            .find(|s| FORBIDDEN_SCHEMES.contains(&s.to_ascii_lowercase().as_str()))
+        {
+            return Err(Error::invalid_input(
+                field,
+                format!("URL scheme '{}' cannot be allowed", scheme),
+            ));
+        }
+        if self.level == SanitizationLevel::Raw && self.role != "administrator" {
+            return Err(Error::invalid_input(
+                field,
+                "Only the administrator policy may skip sanitization",
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn lowercase(names: &[String]) -> HashSet<String> {
+    names.iter().map(|n| n.to_ascii_lowercase()).collect()
+}
+
+/// Per-role sanitization policy
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SanitizationPolicy {
+    /// Sanitize content on save
+    pub enabled: bool,
+    /// Role policies, most privileged first
+    pub roles: Vec<RolePolicy>,
+    /// Policy for users holding none of the listed roles
+    pub fallback: RolePolicy,
+    /// Policy for comments
+    pub comments: RolePolicy,
+    /// Policy for legacy imported HTML
+    pub legacy: RolePolicy,
+}
+
+impl Default for SanitizationPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            roles: vec![
+                RolePolicy::new("administrator", SanitizationLevel::Relaxed),
+                RolePolicy::new("editor", SanitizationLevel::Relaxed).denying(&[
+                    "form", "input", "button", "select", "option", "optgroup", "textarea",
+                    "object", "embed", "style",
+                ]),
+                RolePolicy::new("author", SanitizationLevel::Standard),
+                RolePolicy::new("contributor", SanitizationLevel::Standard),
+            ],
+            fallback: RolePolicy::new("subscriber", SanitizationLevel::Basic),
+            comments: RolePolicy::new("comment", SanitizationLevel::Basic),
+            legacy: RolePolicy::new("legacy", SanitizationLevel::Standard),
+        }
+    }
+}
+
+impl SanitizationPolicy {
+    /// Load the policy from settings, falling back to defaults
+    pub async fn load(pool: &PgPool) -> Result<Self> {
+        SANITIZATION_SETTING.load_or_default(pool).await
+    }
+
+    /// Persist the policy to settings
+    pub async fn save(&self, pool: &PgPool) -> Result<()> {
+        SANITIZATION_SETTING.save(pool, self).await
+    }
+
+    /// Validate allowlists and role names
+    pub fn validate(&self) -> Result<()> {
+        let mut seen = HashSet::new();
+        for policy in &self.roles {
+            if policy.role.trim().is_empty() {
+                return Err(Error::invalid_input("roles", "Role name is required"));
+            }
+            if !seen.insert(policy.role.as_str()) {
+                return Err(Error::invalid_input(
+                    "roles",
+                    format!("Role '{}' is listed twice", policy.role),
+                ));
+            }
+            policy.validate("roles")?;
+        }
+        self.fallback.validate("fallback")?;
+        self.comments.validate("comments")?;
+        self.legacy.validate("legacy")?;
+        if [&self.fallback, &self.comments, &self.legacy]
+            .iter()
+            .any(|p| p.level == SanitizationLevel::Raw)
+        {
+            return Err(Error::invalid_input(
+                "policy",
+                "Fallback, comment and legacy content must be sanitized",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Policy for a user with `roles`
+    pub fn for_roles(&self, roles: &[String]) -> &RolePolicy {
+        self.roles
+            .iter()
+            .find(|policy| roles.contains(&policy.role))
+            .unwrap_or(&self.fallback)
+    }
+}
+
+/// Where sanitized content came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentSource {
+    Post,
+    Comment,
+    Legacy,
+}
+
+impl ContentSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Post => "post",
+            Self::Comment => "comment",
+            Self::Legacy => "legacy",
+        }
+    }
+}
+
+/// Content after a sanitization pass
+#[derive(Debug, Clone, Serialize)]
+pub struct SanitizedContent {
+    pub html: String,
+    /// Policy the content was sanitized with
+    pub policy: String,
+    pub report: SanitizeReport,
+}
+
+/// A stored sanitization report
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct SanitizationReport {
+    pub id: Uuid,
+    pub source: String,
+    /// Post or comment the content belongs to
+    pub subject_id: Option<Uuid>,
+    pub user_id: Option<Uuid>,
+    pub policy: String,
+    pub removed_tags: Json<BTreeMap<String, usize>>,
+    pub removed_attributes: Json<BTreeMap<String, usize>>,
+    pub removed_comments: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Filters for listing reports
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ReportQuery {
+    pub source: Option<ContentSource>,
+    pub subject_id: Option<Uuid>,
+    pub user_id: Option<Uuid>,
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+}
+
+/// One post changed by a legacy sweep
+#[derive(Debug, Clone, Serialize)]
+pub struct LegacySweepItem {
+    pub post_id: Uuid,
+    pub report: SanitizeReport,
+}
+
+/// Result of one legacy sweep batch
+#[derive(Debug, Clone, Serialize)]
+pub struct LegacySweep {
+    pub dry_run: bool,
+    pub scanned: usize,
+    pub changed: Vec<LegacySweepItem>,
+    /// Cursor for the next batch; `None` once every post was scanned
+    pub next_after: Option<Uuid>,
+}
+
+/// Applies the sanitization policy and keeps its reports
+pub struct ContentSanitizationService {
+    pool: PgPool,
+    policy: RwLock<Option<Arc<SanitizationPolicy>>>,
+}
+
+impl ContentSanitizationService {
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            policy: RwLock::new(None),
+        }
+    }
+
+    /// Sanitization policy, loaded from settings on first use
+    pub async fn policy(&self) -> Arc<SanitizationPolicy> {
+        if let Some(policy) = self.policy.read().await.clone() {
+            return policy;
+        }
+
+        match SanitizationPolicy::load(&self.pool).await {
+            Ok(policy) => {
+                let policy = Arc::new(policy);
+                *self.policy.write().await = Some(policy.clone());
+                policy
+            }
+            Err(e) => {
+                // Never let a broken setting switch sanitization off
+                tracing::warn!("Failed to load sanitization policy, using defaults: {}", e);
+                Arc::new(SanitizationPolicy::default())
+            }
+        }
+    }
+
+    /// Replace the cached policy after it was saved
+    pub async fn set_policy(&self, policy: SanitizationPolicy) {
+        *self.policy.write().await = Some(Arc::new(policy));
+    }
+
+    /// Drop the cached policy; the next use reloads it
+    pub async fn invalidate(&self) {
+        *self.policy.write().await = None;
+    }
+
+    /// Sanitize content written by a user with `roles`
+    pub async fn sanitize_for_roles(&self, roles: &[String], html: &str) -> SanitizedContent {
+        let policy = self.policy().await;
+        let role_policy = policy.for_roles(roles);
+        if !policy.enabled {
+            return unchanged(role_policy, html);
+        }
+        run(role_policy, html)
+    }
+
+    /// Sanitize content from outside of the site, such as mail, with the
+    /// fallback policy, whether or not sanitization is enabled
+    pub async fn sanitize_untrusted(&self, html: &str) -> SanitizedContent {
+        run(&self.policy().await.fallback, html)
+    }
+
+    /// Sanitize a comment
+    pub async fn sanitize_comment(&self, html: &str) -> SanitizedContent {
+        let policy = self.policy().await;
+        if !policy.enabled {
+            return unchanged(&policy.comments, html);
+        }
+        run(&policy.comments, html)
+    }
+
+    /// Sanitize with the policy for `role`, for previews; an unknown role
+    /// uses the fallback policy
+    pub async fn preview(&self, role: &str, html: &str) -> SanitizedContent {
+        let policy = self.policy().await;
+        let role_policy = match role {
+            "comment" => &policy.comments,
+            "legacy" => return legacy(&policy.legacy, html),
+            _ => policy.for_roles(&[role.to_string()]),
+        };
+        run(role_policy, html)
+    }
+
+    /// Keep a report of what a pass stripped; clean passes are not recorded
+    pub async fn record(
+        &self,
+        source: ContentSource,
+        subject_id: Option<Uuid>,
+        user_id: Option<Uuid>,
+        content: &SanitizedContent,
+    ) -> Result<()> {
+        if content.report.is_empty() {
+            return Ok(());
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO content_sanitization_reports
+                (id, source, subject_id, user_id, policy, removed_tags, removed_attributes,
+                 removed_comments, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, NOW())
+            "#,
+        )
+        .bind(Uuid::now_v7())
+        .bind(source.as_str())
+        .bind(subject_id)
+        .bind(user_id)
+        .bind(&content.policy)
+        .bind(Json(&content.report.removed_tags))
+        .bind(Json(&content.report.removed_attributes))
+        .bind(content.report.removed_comments.min(i32::MAX as usize) as i32)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to save sanitization report", e))?;
+        Ok(())
+    }
+
+    /// Record a report, logging instead of failing the write it belongs to
+    pub async fn record_quietly(
+        &self,
+        source: ContentSource,
+        subject_id: Option<Uuid>,
+        user_id: Option<Uuid>,
+        content: &SanitizedContent,
+    ) {
+        if let Err(e) = self.record(source, subject_id, user_id, content).await {
+            tracing::warn!("Failed to record sanitization report: {}", e);
+        }
+    }
+
+    /// List reports, newest first
+    pub async fn list_reports(
+        &self,
+        query: &ReportQuery,
+    ) -> Result<(Vec<SanitizationReport>, u64)> {
+        let per_page = query.per_page.unwrap_or(20).clamp(1, 100);
+        let page = query.page.unwrap_or(1).max(1);
+        let source = query.source.map(|s| s.as_str());
+
+        let reports: Vec<SanitizationReport> = sqlx::query_as(
+            r#"
+            SELECT id, source, subject_id, user_id, policy, removed_tags, removed_attributes,
+                   removed_comments, created_at
+            FROM content_sanitization_reports
+            WHERE ($1::text IS NULL OR source = $1)
+              AND ($2::uuid IS NULL OR subject_id = $2)
+              AND ($3::uuid IS NULL OR user_id = $3)
+            ORDER BY created_at DESC
+            LIMIT $4 OFFSET $5
+            "#,
+        )
+        .bind(source)
+        .bind(query.subject_id)
+        .bind(query.user_id)
+        .bind(per_page as i64)
+        .bind(((page - 1) * per_page) as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to list sanitization reports", e))?;
+
+        let (total,): (i64,) = sqlx::query_as(
+            r#"
+            SELECT COUNT(*) FROM content_sanitization_reports
+            WHERE ($1::text IS NULL OR source = $1)
+              AND ($2::uuid IS NULL OR subject_id = $2)
+              AND ($3::uuid IS NULL OR user_id = $3)
+            "#,
+        )
+        .bind(source)
+        .bind(query.subject_id)
+        .bind(query.user_id)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to count sanitization reports", e))?;
+
+        Ok((reports, total as u64))
+    }
+
+    /// Sanitize the legacy HTML of one batch of posts, ordered by id and
+    /// starting after `after`. A dry run only reports what would change.
+    pub async fn sweep_legacy(
+        &self,
+        after: Option<Uuid>,
+        limit: i64,
+        dry_run: bool,
+        user_id: Uuid,
+    ) -> Result<LegacySweep> {
+        let limit = limit.clamp(1, MAX_SWEEP_BATCH);
+        let policy = self.policy().await;
+
+        let posts: Vec<(Uuid, Option<String>)> = sqlx::query_as(
+            r#"
+            SELECT id, content FROM posts
+            WHERE ($1::uuid IS NULL OR id > $1)
+            ORDER BY id
+            LIMIT $2
+            "#,
+        )
+        .bind(after)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load posts for sanitization", e))?;
+
+        let mut changed = Vec::new();
+        for (post_id, content) in &posts {
+            let Some(content) = content.as_deref().filter(|c| !c.is_empty()) else {
+                continue;
+            };
+            let sanitized = legacy(&policy.legacy, content);
+            if sanitized.report.is_empty() {
+                continue;
+            }
+
+            if !dry_run {
+                sqlx::query(
+                    r#"
+                    UPDATE posts SET content = $2, version = version + 1, updated_at = NOW()
+                    WHERE id = $1 AND content = $3
+                    "#,
+                )
+                .bind(post_id)
+                .bind(&sanitized.html)
+                .bind(content)
+                .execute(&self.pool)
+                .await
+                .map_err(|e| Error::database_with_source("Failed to save sanitized post", e))?;
+                self.record(
+                    ContentSource::Legacy,
+                    Some(*post_id),
+                    Some(user_id),
+                    &sanitized,
+                )
+                .await?;
+            }
+            changed.push(LegacySweepItem {
+                post_id: *post_id,
+                report: sanitized.report,
+            });
+        }
+
+        let next_after = if (posts.len() as i64) < limit {
+            None
+        } else {
+            posts.last().map(|(id, _)| *id)
+        };
+        Ok(LegacySweep {
+            dry_run,
+            scanned: posts.len(),
+            changed,
+            next_after,
+        })
+    }
+}
+
+fn run(policy: &RolePolicy, html: &str) -> SanitizedContent {
+    let (html, report) = policy.sanitizer().sanitize_with_report(html);
+    SanitizedContent {
+        html,
+        policy: policy.role.clone(),
+        report,
+    }
+}
+
+fn legacy(policy: &RolePolicy, html: &str) -> SanitizedContent {
+    let (html, report) = sanitize_legacy_html(html, &policy.sanitizer());
+    SanitizedContent {
+        html,
+        policy: policy.role.clone(),
+        report,
+    }
+}
+
+fn unchanged(policy: &RolePolicy, html: &str) -> SanitizedContent {
+    SanitizedContent {
+        html: html.to_string(),
+        policy: policy.role.clone(),
+        report: SanitizeReport::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EMBED: &str = r#"<p>Watch</p><iframe src="https://www.youtube.com/embed/x"></iframe>"#;
+
+    #[test]
+    fn test_role_policies() {
+        let policy = SanitizationPolicy::default();
+
+        let admin = run(policy.for_roles(&["administrator".to_string()]), EMBED);
+        assert_eq!(admin.policy, "administrator");
+        assert!(admin.html.contains("<iframe"));
+        assert!(admin.report.is_empty());
+
+        let contributor = run(policy.for_roles(&["contributor".to_string()]), EMBED);
+        assert!(!contributor.html.contains("<iframe"));
+        assert_eq!(contributor.report.removed_tags.get("iframe"), Some(&1));
+
+        // Users holding several roles get the most privileged listed one
+        let roles = ["subscriber".to_string(), "editor".to_string()];
+        assert_eq!(policy.for_roles(&roles).role, "editor");
+        assert_eq!(policy.for_roles(&[]).role, "subscriber");
+    }
+
+    #[test]
+    fn test_custom_allowlist() {
+        let mut policy = SanitizationPolicy::default();
+        let contributor = policy
+            .roles
+            .iter_mut()
+            .find(|p| p.role == "contributor")
+            .unwrap();
+        contributor.allow_tags.push("iframe".to_string());
+        contributor
+            .allow_attributes
+            .insert("iframe".to_string(), vec!["src".to_string()]);
+        assert!(policy.validate().is_ok());
+
+        let sanitized = run(policy.for_roles(&["contributor".to_string()]), EMBED);
+        assert!(sanitized
+            .html
+            .contains(r#"<iframe src="https://www.youtube.com/embed/x">"#));
+
+        let mut invalid = policy.clone();
+        invalid.roles[0]
+            .allow_attributes
+            .insert("img".to_string(), vec!["onerror".to_string()]);
+        assert!(invalid.validate().is_err());
+
+        let mut invalid = policy.clone();
+        invalid.comments.url_schemes = vec!["JavaScript".to_string()];
+        assert!(invalid.validate().is_err());
+
+        let mut invalid = policy;
+        invalid.roles[2].level = SanitizationLevel::Raw;
+        assert!(invalid.validate().is_err());
+    }
+}
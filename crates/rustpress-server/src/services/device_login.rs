@@ -0,0 +1,317 @@
+//! Device Login
+//!
+//! OAuth2 device authorization grant (RFC 8628) for the RustPress CLI and
+//! other clients without a browser. The client asks for a device code,
+//! shows the user a short user code, and polls while the user approves the
+//! request from a signed-in browser session at `/admin/device`. Once
+//! approved, the poll returns the same access and refresh tokens as a
+//! password login.
+//!
+//! Device codes live for ten minutes. They are kept in the database so any
+//! instance can answer a poll for a code another instance issued, and an
+//! approved code is consumed with a single conditional update so only one
+//! poll ever receives tokens for it.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rustpress_auth::oauth2_provider::{
+    AuthorizationCode, DeviceAuthorization, DeviceAuthorizationStatus, GrantType,
+    InMemoryOAuth2ProviderStore, OAuth2AccessToken, OAuth2Client, OAuth2Provider,
+    OAuth2ProviderConfig, OAuth2ProviderStore, OAuth2RefreshToken,
+};
+use rustpress_core::error::{Error, Result};
+use sqlx::types::Json;
+use sqlx::{FromRow, PgPool};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+/// Client ID of the first-party RustPress CLI
+pub const CLI_CLIENT_ID: &str = "rustpress-cli";
+
+/// Provider handling device authorizations
+pub type DeviceLoginProvider = OAuth2Provider<DeviceLoginStore>;
+
+/// Create the provider with the CLI registered as a public client
+pub fn device_login_provider(pool: PgPool) -> DeviceLoginProvider {
+    let now = Utc::now();
+    let cli = OAuth2Client {
+        id: Uuid::now_v7(),
+        client_id: CLI_CLIENT_ID.to_string(),
+        client_secret_hash: String::new(),
+        name: "RustPress CLI".to_string(),
+        description: Some("Command-line interface".to_string()),
+        redirect_uris: vec![],
+        allowed_scopes: ["*".to_string()].into_iter().collect(),
+        grant_types: [GrantType::DeviceCode].into_iter().collect(),
+        is_confidential: false,
+        is_active: true,
+        owner_id: None,
+        created_at: now,
+        updated_at: now,
+    };
+
+    OAuth2Provider::new(
+        DeviceLoginStore::new(pool, InMemoryOAuth2ProviderStore::new().with_client(cli)),
+        OAuth2ProviderConfig::default(),
+    )
+}
+
+const DEVICE_COLUMNS: &str = "id, device_code_hash, user_code, client_id, scopes, status, \
+     user_id, interval_secs, last_polled_at, expires_at, created_at";
+
+#[derive(Debug, FromRow)]
+struct DeviceAuthorizationRow {
+    id: Uuid,
+    device_code_hash: String,
+    user_code: String,
+    client_id: String,
+    scopes: Json<HashSet<String>>,
+    status: String,
+    user_id: Option<Uuid>,
+    interval_secs: i64,
+    last_polled_at: Option<DateTime<Utc>>,
+    expires_at: DateTime<Utc>,
+    created_at: DateTime<Utc>,
+}
+
+impl DeviceAuthorizationRow {
+    fn into_authorization(self) -> Result<DeviceAuthorization> {
+        let status = serde_json::from_value(serde_json::Value::String(self.status))
+            .map_err(|e| Error::internal(format!("Invalid device authorization status: {}", e)))?;
+        Ok(DeviceAuthorization {
+            id: self.id,
+            device_code_hash: self.device_code_hash,
+            user_code: self.user_code,
+            client_id: self.client_id,
+            scopes: self.scopes.0,
+            status,
+            user_id: self.user_id,
+            interval_secs: self.interval_secs,
+            last_polled_at: self.last_polled_at,
+            expires_at: self.expires_at,
+            created_at: self.created_at,
+        })
+    }
+}
+
+fn status_name(status: DeviceAuthorizationStatus) -> &'static str {
+    match status {
+        DeviceAuthorizationStatus::Pending => "pending",
+        DeviceAuthorizationStatus::Approved => "approved",
+        DeviceAuthorizationStatus::Denied => "denied",
+        DeviceAuthorizationStatus::Consumed => "consumed",
+    }
+}
+
+/// Store for the device login provider
+///
+/// Device authorizations are persisted in `oauth_device_authorizations`;
+/// clients and tokens stay in memory, since the only client is the built-in
+/// CLI and tokens are issued as regular session JWTs.
+pub struct DeviceLoginStore {
+    pool: PgPool,
+    clients: InMemoryOAuth2ProviderStore,
+}
+
+impl DeviceLoginStore {
+    pub fn new(pool: PgPool, clients: InMemoryOAuth2ProviderStore) -> Self {
+        Self { pool, clients }
+    }
+
+    async fn find_by(&self, column: &str, value: &str) -> Result<Option<DeviceAuthorization>> {
+        let row: Option<DeviceAuthorizationRow> = sqlx::query_as(&format!(
+            "SELECT {} FROM oauth_device_authorizations WHERE {} = $1 \
+             ORDER BY created_at DESC LIMIT 1",
+            DEVICE_COLUMNS, column
+        ))
+        .bind(value)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load device authorization", e))?;
+        row.map(DeviceAuthorizationRow::into_authorization)
+            .transpose()
+    }
+}
+
+#[async_trait]
+impl OAuth2ProviderStore for DeviceLoginStore {
+    async fn create_client(&self, client: &OAuth2Client) -> Result<()> {
+        self.clients.create_client(client).await
+    }
+
+    async fn get_client_by_id(&self, client_id: &str) -> Result<Option<OAuth2Client>> {
+        self.clients.get_client_by_id(client_id).await
+    }
+
+    async fn update_client(&self, client: &OAuth2Client) -> Result<()> {
+        self.clients.update_client(client).await
+    }
+
+    async fn delete_client(&self, client_id: &str) -> Result<()> {
+        self.clients.delete_client(client_id).await
+    }
+
+    async fn store_auth_code(&self, code: &AuthorizationCode) -> Result<()> {
+        self.clients.store_auth_code(code).await
+    }
+
+    async fn get_auth_code(&self, code_hash: &str) -> Result<Option<AuthorizationCode>> {
+        self.clients.get_auth_code(code_hash).await
+    }
+
+    async fn mark_auth_code_used(&self, id: Uuid) -> Result<()> {
+        self.clients.mark_auth_code_used(id).await
+    }
+
+    async fn store_access_token(&self, token: &OAuth2AccessToken) -> Result<()> {
+        self.clients.store_access_token(token).await
+    }
+
+    async fn get_access_token(&self, token_hash: &str) -> Result<Option<OAuth2AccessToken>> {
+        self.clients.get_access_token(token_hash).await
+    }
+
+    async fn revoke_access_token(&self, id: Uuid) -> Result<()> {
+        self.clients.revoke_access_token(id).await
+    }
+
+    async fn revoke_client_tokens(&self, client_id: &str) -> Result<u64> {
+        self.clients.revoke_client_tokens(client_id).await
+    }
+
+    async fn store_refresh_token(&self, token: &OAuth2RefreshToken) -> Result<()> {
+        self.clients.store_refresh_token(token).await
+    }
+
+    async fn get_refresh_token(&self, token_hash: &str) -> Result<Option<OAuth2RefreshToken>> {
+        self.clients.get_refresh_token(token_hash).await
+    }
+
+    async fn revoke_refresh_token(&self, id: Uuid) -> Result<()> {
+        self.clients.revoke_refresh_token(id).await
+    }
+
+    async fn store_device_authorization(&self, auth: &DeviceAuthorization) -> Result<()> {
+        // Expired codes are only kept until the next one is issued
+        self.cleanup_expired().await?;
+        sqlx::query(
+            r#"
+            INSERT INTO oauth_device_authorizations
+                (id, device_code_hash, user_code, client_id, scopes, status, user_id,
+                 interval_secs, last_polled_at, expires_at, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            "#,
+        )
+        .bind(auth.id)
+        .bind(&auth.device_code_hash)
+        .bind(&auth.user_code)
+        .bind(&auth.client_id)
+        .bind(Json(&auth.scopes))
+        .bind(status_name(auth.status))
+        .bind(auth.user_id)
+        .bind(auth.interval_secs)
+        .bind(auth.last_polled_at)
+        .bind(auth.expires_at)
+        .bind(auth.created_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to store device authorization", e))?;
+        Ok(())
+    }
+
+    async fn get_device_authorization(
+        &self,
+        device_code_hash: &str,
+    ) -> Result<Option<DeviceAuthorization>> {
+        self.find_by("device_code_hash", device_code_hash).await
+    }
+
+    async fn get_device_authorization_by_user_code(
+        &self,
+        user_code: &str,
+    ) -> Result<Option<DeviceAuthorization>> {
+        self.find_by("user_code", user_code).await
+    }
+
+    async fn update_device_authorization(&self, auth: &DeviceAuthorization) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE oauth_device_authorizations
+            SET status = $2, user_id = $3, interval_secs = $4, last_polled_at = $5
+            WHERE id = $1
+            "#,
+        )
+        .bind(auth.id)
+        .bind(status_name(auth.status))
+        .bind(auth.user_id)
+        .bind(auth.interval_secs)
+        .bind(auth.last_polled_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to update device authorization", e))?;
+        Ok(())
+    }
+
+    async fn record_device_poll(
+        &self,
+        id: Uuid,
+        polled_at: DateTime<Utc>,
+        interval_secs: i64,
+    ) -> Result<()> {
+        sqlx::query(
+            "UPDATE oauth_device_authorizations \
+             SET last_polled_at = $2, interval_secs = $3 WHERE id = $1",
+        )
+        .bind(id)
+        .bind(polled_at)
+        .bind(interval_secs)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to record device poll", e))?;
+        Ok(())
+    }
+
+    async fn consume_device_authorization(&self, id: Uuid) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE oauth_device_authorizations SET status = 'consumed' \
+             WHERE id = $1 AND status = 'approved'",
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to consume device authorization", e))?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn cleanup_expired(&self) -> Result<u64> {
+        let removed =
+            sqlx::query("DELETE FROM oauth_device_authorizations WHERE expires_at < NOW()")
+                .execute(&self.pool)
+                .await
+                .map_err(|e| {
+                    Error::database_with_source("Failed to purge device authorizations", e)
+                })?
+                .rows_affected();
+        Ok(removed + self.clients.cleanup_expired().await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_names_match_serde() {
+        for status in [
+            DeviceAuthorizationStatus::Pending,
+            DeviceAuthorizationStatus::Approved,
+            DeviceAuthorizationStatus::Denied,
+            DeviceAuthorizationStatus::Consumed,
+        ] {
+            assert_eq!(
+                serde_json::to_value(status).unwrap(),
+                serde_json::Value::String(status_name(status).to_string())
+            );
+        }
+    }
+}
@@ -0,0 +1,537 @@
+//! Editorial Discussions
+//!
+//! Internal comment threads on drafts, stored apart from public comments
+//! and never shown on the site. A thread is about one editor block,
+//! identified by the client ID the editor gave it, or about the post as a
+//! whole, and may quote the text it refers to. Writing `@username` in a
+//! comment mentions that user, who is sent a live notification.
+//!
+//! Threads can only be opened while a post is unpublished, but stay
+//! readable and open to replies afterwards. Once dealt with, a thread is
+//! resolved; it can be reopened at any time.
+
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use rustpress_core::error::{Error, Result};
+use rustpress_core::tenant::site_filter;
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use sqlx::{FromRow, PgPool};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::LazyLock;
+use uuid::Uuid;
+
+/// Longest accepted comment
+pub const MAX_CONTENT_LENGTH: usize = 10_000;
+
+/// Longest quoted text kept with a thread
+const MAX_QUOTE_LENGTH: usize = 1_000;
+
+/// Longest accepted block client ID
+const MAX_CLIENT_ID_LENGTH: usize = 64;
+
+/// Most users one comment can mention
+const MAX_MENTIONS: usize = 20;
+
+/// Post statuses threads can't be opened in
+const CLOSED_STATUSES: [&str; 2] = ["published", "trash"];
+
+/// `@username`, not preceded by a word character so email addresses
+/// don't count
+static MENTION_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?:^|[^\w@])@([A-Za-z0-9_][A-Za-z0-9._-]*)").unwrap());
+
+const THREAD_COLUMNS: &str = "id, post_id, block_client_id, quoted_text, status, created_by, \
+     resolved_by, resolved_at, created_at, updated_at";
+
+const COMMENT_COLUMNS: &str = "c.id, c.thread_id, c.author_id, u.username AS author_username, \
+     u.display_name AS author_name, c.content, c.mentions, c.created_at, c.updated_at";
+
+/// Whether a thread still needs attention
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThreadStatus {
+    Open,
+    Resolved,
+}
+
+impl ThreadStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Open => "open",
+            Self::Resolved => "resolved",
+        }
+    }
+}
+
+/// A discussion thread with its comments, oldest first
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct DiscussionThread {
+    pub id: Uuid,
+    pub post_id: Uuid,
+    /// Client ID of the block the thread is about; none for the whole post
+    pub block_client_id: Option<String>,
+    pub quoted_text: Option<String>,
+    pub status: String,
+    pub created_by: Option<Uuid>,
+    pub resolved_by: Option<Uuid>,
+    pub resolved_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    #[sqlx(skip)]
+    pub comments: Vec<DiscussionComment>,
+}
+
+/// A comment in a thread
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct DiscussionComment {
+    pub id: Uuid,
+    pub thread_id: Uuid,
+    pub author_id: Option<Uuid>,
+    pub author_username: Option<String>,
+    pub author_name: Option<String>,
+    pub content: String,
+    /// Users mentioned in the comment
+    pub mentions: Json<Vec<Uuid>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A new thread as submitted
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ThreadInput {
+    pub block_client_id: Option<String>,
+    pub quoted_text: Option<String>,
+    /// The first comment
+    pub content: String,
+}
+
+impl ThreadInput {
+    /// Check the thread, trimming its fields and dropping empty ones
+    pub fn normalize(mut self) -> Result<Self> {
+        self.block_client_id = self
+            .block_client_id
+            .map(|id| id.trim().to_string())
+            .filter(|id| !id.is_empty());
+        if let Some(id) = &self.block_client_id {
+            let valid = id.len() <= MAX_CLIENT_ID_LENGTH
+                && id
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+            if !valid {
+                return Err(Error::invalid_input(
+                    "block_client_id",
+                    format!(
+                        "Must be at most {} letters, digits, dashes or underscores",
+                        MAX_CLIENT_ID_LENGTH
+                    ),
+                ));
+            }
+        }
+
+        self.quoted_text = self
+            .quoted_text
+            .map(|text| text.trim().to_string())
+            .filter(|text| !text.is_empty());
+        if let Some(text) = &mut self.quoted_text {
+            if let Some((end, _)) = text.char_indices().nth(MAX_QUOTE_LENGTH) {
+                text.truncate(end);
+            }
+        }
+
+        self.content = normalize_content(&self.content)?;
+        Ok(self)
+    }
+}
+
+/// A reply or edited comment as submitted
+#[derive(Debug, Clone, Deserialize)]
+pub struct CommentInput {
+    pub content: String,
+}
+
+impl CommentInput {
+    pub fn normalize(mut self) -> Result<Self> {
+        self.content = normalize_content(&self.content)?;
+        Ok(self)
+    }
+}
+
+fn normalize_content(content: &str) -> Result<String> {
+    let content = content.trim();
+    if content.is_empty() || content.chars().count() > MAX_CONTENT_LENGTH {
+        return Err(Error::invalid_input(
+            "content",
+            format!("Must be 1 to {} characters", MAX_CONTENT_LENGTH),
+        ));
+    }
+    Ok(content.to_string())
+}
+
+/// Which threads to list
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThreadFilter {
+    #[default]
+    Open,
+    Resolved,
+    All,
+}
+
+/// Thread list filters
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DiscussionQuery {
+    #[serde(default)]
+    pub status: ThreadFilter,
+    /// Only threads about this block
+    pub block_client_id: Option<String>,
+}
+
+/// Usernames mentioned in a comment, lowercased, in order and without
+/// repeats
+pub fn parse_mentions(content: &str) -> Vec<String> {
+    let mut seen = BTreeSet::new();
+    MENTION_RE
+        .captures_iter(content)
+        .map(|caps| caps[1].trim_end_matches(['.', '-']).to_lowercase())
+        .filter(|username| !username.is_empty() && seen.insert(username.clone()))
+        .take(MAX_MENTIONS)
+        .collect()
+}
+
+/// Editorial discussions
+pub struct DiscussionService {
+    pool: PgPool,
+}
+
+impl DiscussionService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// A post's threads, oldest first
+    pub async fn list(
+        &self,
+        post_id: Uuid,
+        query: &DiscussionQuery,
+    ) -> Result<Vec<DiscussionThread>> {
+        let status = match query.status {
+            ThreadFilter::Open => Some(ThreadStatus::Open.as_str()),
+            ThreadFilter::Resolved => Some(ThreadStatus::Resolved.as_str()),
+            ThreadFilter::All => None,
+        };
+        let block_client_id = query
+            .block_client_id
+            .as_deref()
+            .map(str::trim)
+            .filter(|id| !id.is_empty());
+
+        let mut threads: Vec<DiscussionThread> = sqlx::query_as(&format!(
+            "SELECT {} FROM discussion_threads WHERE post_id = $1 \
+             AND ($2::text IS NULL OR status = $2) \
+             AND ($3::text IS NULL OR block_client_id = $3) \
+             ORDER BY created_at, id",
+            THREAD_COLUMNS
+        ))
+        .bind(post_id)
+        .bind(status)
+        .bind(block_client_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to list discussions", e))?;
+
+        let ids: Vec<Uuid> = threads.iter().map(|t| t.id).collect();
+        let mut comments = self.comments(&ids).await?;
+        for thread in &mut threads {
+            thread.comments = comments.remove(&thread.id).unwrap_or_default();
+        }
+        Ok(threads)
+    }
+
+    pub async fn get(&self, id: Uuid) -> Result<DiscussionThread> {
+        let mut thread: DiscussionThread = sqlx::query_as(&format!(
+            "SELECT {} FROM discussion_threads WHERE id = $1",
+            THREAD_COLUMNS
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load discussion", e))?
+        .ok_or_else(|| Error::not_found("Discussion", id.to_string()))?;
+        thread.comments = self.comments(&[id]).await?.remove(&id).unwrap_or_default();
+        Ok(thread)
+    }
+
+    /// Open a thread on an unpublished post
+    pub async fn open(
+        &self,
+        post_id: Uuid,
+        author_id: Uuid,
+        input: ThreadInput,
+    ) -> Result<DiscussionThread> {
+        let input = input.normalize()?;
+        let status: String = sqlx::query_scalar(&format!(
+            "SELECT status FROM posts WHERE id = $1 AND {}",
+            site_filter("site_id")
+        ))
+        .bind(post_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load post", e))?
+        .ok_or_else(|| Error::not_found("Post", post_id.to_string()))?;
+        if CLOSED_STATUSES.contains(&status.as_str()) {
+            return Err(Error::validation(
+                "Discussions can only be opened on unpublished posts",
+            ));
+        }
+        let mentions = self.resolve_mentions(&input.content).await?;
+
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| Error::database_with_source("Failed to start transaction", e))?;
+        let thread_id = Uuid::now_v7();
+        sqlx::query(
+            "INSERT INTO discussion_threads (id, post_id, block_client_id, quoted_text, created_by) \
+             VALUES ($1, $2, $3, $4, $5)",
+        )
+        .bind(thread_id)
+        .bind(post_id)
+        .bind(&input.block_client_id)
+        .bind(&input.quoted_text)
+        .bind(author_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to save discussion", e))?;
+        sqlx::query(
+            "INSERT INTO discussion_comments (id, thread_id, author_id, content, mentions) \
+             VALUES ($1, $2, $3, $4, $5)",
+        )
+        .bind(Uuid::now_v7())
+        .bind(thread_id)
+        .bind(author_id)
+        .bind(&input.content)
+        .bind(Json(&mentions))
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to save comment", e))?;
+        tx.commit()
+            .await
+            .map_err(|e| Error::database_with_source("Failed to commit discussion", e))?;
+
+        self.get(thread_id).await
+    }
+
+    /// Add a comment to a thread
+    pub async fn reply(
+        &self,
+        thread_id: Uuid,
+        author_id: Uuid,
+        input: CommentInput,
+    ) -> Result<DiscussionComment> {
+        let input = input.normalize()?;
+        let mentions = self.resolve_mentions(&input.content).await?;
+        let touched = sqlx::query("UPDATE discussion_threads SET updated_at = NOW() WHERE id = $1")
+            .bind(thread_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to update discussion", e))?;
+        if touched.rows_affected() == 0 {
+            return Err(Error::not_found("Discussion", thread_id.to_string()));
+        }
+
+        let id = Uuid::now_v7();
+        sqlx::query(
+            "INSERT INTO discussion_comments (id, thread_id, author_id, content, mentions) \
+             VALUES ($1, $2, $3, $4, $5)",
+        )
+        .bind(id)
+        .bind(thread_id)
+        .bind(author_id)
+        .bind(&input.content)
+        .bind(Json(&mentions))
+        .execute(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to save comment", e))?;
+        self.comment(id).await
+    }
+
+    pub async fn comment(&self, id: Uuid) -> Result<DiscussionComment> {
+        sqlx::query_as(&format!(
+            "SELECT {} FROM discussion_comments c LEFT JOIN users u ON u.id = c.author_id \
+             WHERE c.id = $1",
+            COMMENT_COLUMNS
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load comment", e))?
+        .ok_or_else(|| Error::not_found("Discussion comment", id.to_string()))
+    }
+
+    /// Change a comment's text, mentioning anyone it now names
+    pub async fn edit(&self, id: Uuid, input: CommentInput) -> Result<DiscussionComment> {
+        let input = input.normalize()?;
+        let mentions = self.resolve_mentions(&input.content).await?;
+        let updated = sqlx::query(
+            "UPDATE discussion_comments SET content = $2, mentions = $3, updated_at = NOW() \
+             WHERE id = $1",
+        )
+        .bind(id)
+        .bind(&input.content)
+        .bind(Json(&mentions))
+        .execute(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to save comment", e))?;
+        if updated.rows_affected() == 0 {
+            return Err(Error::not_found("Discussion comment", id.to_string()));
+        }
+        self.comment(id).await
+    }
+
+    /// Delete a reply. A thread's first comment goes with the thread.
+    pub async fn delete_comment(&self, id: Uuid) -> Result<()> {
+        let comment = self.comment(id).await?;
+        let first: Uuid = sqlx::query_scalar(
+            "SELECT id FROM discussion_comments WHERE thread_id = $1 \
+             ORDER BY created_at, id LIMIT 1",
+        )
+        .bind(comment.thread_id)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load discussion", e))?;
+        if first == id {
+            return Err(Error::validation(
+                "The first comment of a discussion can only be deleted with the discussion",
+            ));
+        }
+
+        sqlx::query("DELETE FROM discussion_comments WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to delete comment", e))?;
+        Ok(())
+    }
+
+    pub async fn delete(&self, id: Uuid) -> Result<()> {
+        let deleted = sqlx::query("DELETE FROM discussion_threads WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to delete discussion", e))?;
+        if deleted.rows_affected() == 0 {
+            return Err(Error::not_found("Discussion", id.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Resolve or reopen a thread
+    pub async fn set_status(
+        &self,
+        id: Uuid,
+        status: ThreadStatus,
+        user_id: Uuid,
+    ) -> Result<DiscussionThread> {
+        let resolved = status == ThreadStatus::Resolved;
+        let updated = sqlx::query(
+            "UPDATE discussion_threads SET status = $2, \
+             resolved_by = CASE WHEN $3 THEN $4 END, \
+             resolved_at = CASE WHEN $3 THEN NOW() END, \
+             updated_at = NOW() \
+             WHERE id = $1",
+        )
+        .bind(id)
+        .bind(status.as_str())
+        .bind(resolved)
+        .bind(user_id)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to update discussion", e))?;
+        if updated.rows_affected() == 0 {
+            return Err(Error::not_found("Discussion", id.to_string()));
+        }
+        self.get(id).await
+    }
+
+    /// Comments of the threads, by thread, oldest first
+    async fn comments(&self, thread_ids: &[Uuid]) -> Result<HashMap<Uuid, Vec<DiscussionComment>>> {
+        if thread_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let rows: Vec<DiscussionComment> = sqlx::query_as(&format!(
+            "SELECT {} FROM discussion_comments c LEFT JOIN users u ON u.id = c.author_id \
+             WHERE c.thread_id = ANY($1) ORDER BY c.created_at, c.id",
+            COMMENT_COLUMNS
+        ))
+        .bind(thread_ids)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load discussion comments", e))?;
+
+        let mut comments: HashMap<Uuid, Vec<DiscussionComment>> = HashMap::new();
+        for comment in rows {
+            comments.entry(comment.thread_id).or_default().push(comment);
+        }
+        Ok(comments)
+    }
+
+    /// Active users mentioned in a comment; unknown usernames are ignored
+    async fn resolve_mentions(&self, content: &str) -> Result<Vec<Uuid>> {
+        let usernames = parse_mentions(content);
+        if usernames.is_empty() {
+            return Ok(Vec::new());
+        }
+        sqlx::query_scalar(
+            "SELECT id FROM users WHERE LOWER(username) = ANY($1) \
+             AND status = 'active' AND deleted_at IS NULL ORDER BY id",
+        )
+        .bind(&usernames)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to look up mentioned users", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mentions() {
+        assert_eq!(
+            parse_mentions("@alice can you check this? cc @Bob.Smith, @alice."),
+            ["alice", "bob.smith"]
+        );
+        assert!(parse_mentions("mail editor@example.com or @@").is_empty());
+        assert_eq!(parse_mentions("(@carol) and\n@dave-"), ["carol", "dave"]);
+    }
+
+    #[test]
+    fn test_normalize_thread_input() {
+        let input = ThreadInput {
+            block_client_id: Some(" 3f2a-block_1 ".to_string()),
+            quoted_text: Some("   ".to_string()),
+            content: "  Needs a source  ".to_string(),
+        }
+        .normalize()
+        .unwrap();
+        assert_eq!(input.block_client_id.as_deref(), Some("3f2a-block_1"));
+        assert_eq!(input.quoted_text, None);
+        assert_eq!(input.content, "Needs a source");
+
+        let bad_client_id = ThreadInput {
+            block_client_id: Some("<script>".to_string()),
+            content: "Hi".to_string(),
+            ..Default::default()
+        };
+        assert!(bad_client_id.normalize().is_err());
+
+        let empty = ThreadInput {
+            content: " ".to_string(),
+            ..Default::default()
+        };
+        assert!(empty.normalize().is_err());
+    }
+}
@@ -71,6 +71,8 @@ pub enum EmailTemplate {
     PostPublished,
     AccountDeactivated,
     SecurityAlert,
+    Invitation,
+    GroupNotification,
 }
 
 impl EmailTemplate {
@@ -84,6 +86,8 @@ impl EmailTemplate {
             Self::PostPublished => "Your Post Has Been Published",
             Self::AccountDeactivated => "Your Account Has Been Deactivated",
             Self::SecurityAlert => "Security Alert for Your Account",
+            Self::Invitation => "You're Invited to {{site_name}}",
+            Self::GroupNotification => "A Message for Your Team on {{site_name}}",
         }
     }
 
@@ -97,6 +101,10 @@ impl EmailTemplate {
             Self::PostPublished => include_str!("../templates/email/post_published.html"),
             Self::AccountDeactivated => include_str!("../templates/email/account_deactivated.html"),
             Self::SecurityAlert => include_str!("../templates/email/security_alert.html"),
+            Self::Invitation => include_str!("../templates/email/invitation.html"),
+            Self::GroupNotification => {
+                include_str!("../templates/email/group_notification.html")
+            }
         }
     }
 }
@@ -179,6 +187,8 @@ impl EmailService {
                 EmailTemplate::PostPublished,
                 EmailTemplate::AccountDeactivated,
                 EmailTemplate::SecurityAlert,
+                EmailTemplate::Invitation,
+                EmailTemplate::GroupNotification,
             ] {
                 let name = format!("{:?}", template);
                 if let Err(e) = templates.register_template_string(&name, template.template_html())
@@ -330,6 +340,65 @@ impl EmailService {
             .await
     }
 
+    /// Send an invitation with a link to set the account's password
+    pub async fn send_invitation(
+        &self,
+        email: &str,
+        name: Option<&str>,
+        token: &str,
+    ) -> Result<EmailResult, EmailError> {
+        let config = self.config.read().await;
+        let set_password_url = format!("{}/reset-password?token={}", config.site_url, token);
+        drop(config);
+
+        let mut data = HashMap::new();
+        data.insert(
+            "name".to_string(),
+            serde_json::json!(name.unwrap_or("User")),
+        );
+        data.insert("reset_url".to_string(), serde_json::json!(set_password_url));
+        data.insert(
+            "expires_days".to_string(),
+            serde_json::json!(super::user_import::INVITATION_DAYS),
+        );
+
+        self.send_template(EmailTemplate::Invitation, email, name, data)
+            .await
+    }
+
+    /// Send a message addressed to a group to one of its members
+    pub async fn send_group_notification(
+        &self,
+        email: &str,
+        name: Option<&str>,
+        group_name: &str,
+        title: &str,
+        message: &str,
+        link: Option<&str>,
+    ) -> Result<EmailResult, EmailError> {
+        let mut data = HashMap::new();
+        data.insert(
+            "name".to_string(),
+            serde_json::json!(name.unwrap_or("User")),
+        );
+        data.insert("group_name".to_string(), serde_json::json!(group_name));
+        data.insert("title".to_string(), serde_json::json!(title));
+        data.insert("message".to_string(), serde_json::json!(message));
+        if let Some(link) = link {
+            // Site paths are made absolute for the mail client
+            let link = if link.starts_with('/') {
+                let config = self.config.read().await;
+                format!("{}{}", config.site_url.trim_end_matches('/'), link)
+            } else {
+                link.to_string()
+            };
+            data.insert("link".to_string(), serde_json::json!(link));
+        }
+
+        self.send_template(EmailTemplate::GroupNotification, email, name, data)
+            .await
+    }
+
     /// Send email verification email
     pub async fn send_email_verification(
         &self,
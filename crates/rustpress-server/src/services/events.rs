@@ -0,0 +1,1914 @@
+//! Events
+//!
+//! Posts become events when a schedule is attached:
+//!
+//! - an event is a post of type `event`, served at `/event/{slug}` and
+//!   listed by the `/events` and `/events/past` archives, which themes style
+//!   through `single-event` and `archive-event` templates. Its schedule,
+//!   venue, organizer and tickets are kept in `event_details`
+//! - a schedule is a first occurrence in an IANA timezone, optionally
+//!   repeated by an RFC 5545 recurrence rule with excluded dates. Rules are
+//!   expanded in local time, so a weekly 19:00 meetup stays at 19:00 across
+//!   daylight saving changes
+//! - event pages carry schema.org `Event` data for the next occurrence, and
+//!   `/events.ics` publishes every event as an iCalendar feed
+
+use chrono::{
+    DateTime, Datelike, Duration, Month, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc,
+    Weekday,
+};
+use chrono_tz::Tz;
+use rustpress_core::error::{Error, Result};
+use rustpress_core::tenant::site_filter;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sqlx::types::Json;
+use sqlx::{FromRow, PgPool};
+use std::collections::VecDeque;
+use std::fmt;
+use std::str::FromStr;
+use uuid::Uuid;
+
+use super::podcast::is_web_url;
+use super::render_service::PostData;
+
+/// Post type of events
+pub const EVENT_POST_TYPE: &str = "event";
+
+/// Occurrences a recurrence rule may produce through `COUNT`
+const MAX_COUNT: u32 = 1_000;
+
+/// Largest `INTERVAL` of a recurrence rule
+const MAX_INTERVAL: u32 = 366;
+
+/// Periods (days, weeks, months or years) a rule is expanded over before
+/// giving up, so rules that never match cannot loop forever
+const MAX_PERIODS: u32 = 50_000;
+
+/// Dates an event may exclude from its series
+const MAX_EXDATES: usize = 500;
+
+/// Longest single occurrence
+const MAX_EVENT_DAYS: i64 = 366;
+
+/// Upcoming occurrences templates get for recurring events
+const UPCOMING_OCCURRENCES: usize = 5;
+
+/// Days a calendar view may span
+const MAX_CALENDAR_DAYS: i64 = 366;
+
+/// Days a calendar view spans by default
+const DEFAULT_CALENDAR_DAYS: i64 = 31;
+
+/// Longest venue, organizer or price text
+const MAX_NAME_LENGTH: usize = 255;
+
+/// How often a rule repeats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+impl Frequency {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Daily => "DAILY",
+            Self::Weekly => "WEEKLY",
+            Self::Monthly => "MONTHLY",
+            Self::Yearly => "YEARLY",
+        }
+    }
+}
+
+/// A `BYDAY` entry: a weekday, or its nth (or nth-last) occurrence in the
+/// month
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByDay {
+    pub ordinal: Option<i32>,
+    pub weekday: Weekday,
+}
+
+/// End of a series as written in `UNTIL`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Until {
+    Date(NaiveDate),
+    Local(NaiveDateTime),
+    Utc(DateTime<Utc>),
+}
+
+/// An RFC 5545 recurrence rule.
+///
+/// `FREQ`, `INTERVAL`, `COUNT`, `UNTIL`, `BYDAY`, `BYMONTHDAY`, `BYMONTH`
+/// and `WKST=MO` are supported; rules using other parts are rejected rather
+/// than expanded differently than calendar clients would.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecurrenceRule {
+    pub frequency: Frequency,
+    pub interval: u32,
+    pub count: Option<u32>,
+    pub until: Option<Until>,
+    pub by_day: Vec<ByDay>,
+    pub by_month_day: Vec<i32>,
+    pub by_month: Vec<u32>,
+}
+
+fn rule_error(message: impl Into<String>) -> Error {
+    Error::invalid_input("rrule", message)
+}
+
+fn parse_weekday(code: &str) -> Option<Weekday> {
+    Some(match code {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return None,
+    })
+}
+
+fn weekday_code(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "MO",
+        Weekday::Tue => "TU",
+        Weekday::Wed => "WE",
+        Weekday::Thu => "TH",
+        Weekday::Fri => "FR",
+        Weekday::Sat => "SA",
+        Weekday::Sun => "SU",
+    }
+}
+
+fn weekday_name(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "Monday",
+        Weekday::Tue => "Tuesday",
+        Weekday::Wed => "Wednesday",
+        Weekday::Thu => "Thursday",
+        Weekday::Fri => "Friday",
+        Weekday::Sat => "Saturday",
+        Weekday::Sun => "Sunday",
+    }
+}
+
+fn parse_numbers<T: FromStr>(key: &str, value: &str) -> Result<Vec<T>> {
+    value
+        .split(',')
+        .map(|n| {
+            n.trim_start_matches('+')
+                .parse()
+                .map_err(|_| rule_error(format!("Invalid {} value '{}'", key, n)))
+        })
+        .collect()
+}
+
+fn parse_until(value: &str) -> Result<Until> {
+    if let Some(utc) = value.strip_suffix('Z') {
+        return NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S")
+            .map(|until| Until::Utc(until.and_utc()))
+            .map_err(|_| rule_error(format!("Invalid UNTIL '{}'", value)));
+    }
+    if value.contains('T') {
+        return NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+            .map(Until::Local)
+            .map_err(|_| rule_error(format!("Invalid UNTIL '{}'", value)));
+    }
+    NaiveDate::parse_from_str(value, "%Y%m%d")
+        .map(Until::Date)
+        .map_err(|_| rule_error(format!("Invalid UNTIL '{}'", value)))
+}
+
+impl FromStr for RecurrenceRule {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let s = s.strip_prefix("RRULE:").unwrap_or(s);
+
+        let mut frequency = None;
+        let mut rule = RecurrenceRule {
+            frequency: Frequency::Daily,
+            interval: 1,
+            count: None,
+            until: None,
+            by_day: Vec::new(),
+            by_month_day: Vec::new(),
+            by_month: Vec::new(),
+        };
+        for part in s.split(';').filter(|p| !p.is_empty()) {
+            let (key, value) = part
+                .split_once('=')
+                .ok_or_else(|| rule_error(format!("Invalid rule part '{}'", part)))?;
+            let key = key.to_ascii_uppercase();
+            let value = value.to_ascii_uppercase();
+            match key.as_str() {
+                "FREQ" => {
+                    frequency = Some(match value.as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        _ => return Err(rule_error(format!("FREQ={} is not supported", value))),
+                    })
+                }
+                "INTERVAL" => {
+                    rule.interval = value
+                        .parse()
+                        .map_err(|_| rule_error(format!("Invalid INTERVAL '{}'", value)))?
+                }
+                "COUNT" => {
+                    rule.count = Some(
+                        value
+                            .parse()
+                            .map_err(|_| rule_error(format!("Invalid COUNT '{}'", value)))?,
+                    )
+                }
+                "UNTIL" => rule.until = Some(parse_until(&value)?),
+                "BYDAY" => {
+                    rule.by_day = value
+                        .split(',')
+                        .map(|day| {
+                            let split = day.len().saturating_sub(2);
+                            let weekday = day
+                                .get(split..)
+                                .and_then(parse_weekday)
+                                .ok_or_else(|| rule_error(format!("Invalid BYDAY '{}'", day)))?;
+                            let ordinal =
+                                match &day[..split] {
+                                    "" => None,
+                                    n => Some(n.trim_start_matches('+').parse().map_err(|_| {
+                                        rule_error(format!("Invalid BYDAY '{}'", day))
+                                    })?),
+                                };
+                            Ok(ByDay { ordinal, weekday })
+                        })
+                        .collect::<Result<_>>()?
+                }
+                "BYMONTHDAY" => rule.by_month_day = parse_numbers("BYMONTHDAY", &value)?,
+                "BYMONTH" => rule.by_month = parse_numbers("BYMONTH", &value)?,
+                "WKST" if value == "MO" => {}
+                _ => {
+                    return Err(rule_error(format!(
+                        "Recurrence rule part '{}' is not supported",
+                        key
+                    )))
+                }
+            }
+        }
+        rule.frequency = frequency.ok_or_else(|| rule_error("The rule needs a FREQ"))?;
+        rule.validate()?;
+        Ok(rule)
+    }
+}
+
+impl fmt::Display for RecurrenceRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FREQ={}", self.frequency.as_str())?;
+        if self.interval > 1 {
+            write!(f, ";INTERVAL={}", self.interval)?;
+        }
+        if let Some(count) = self.count {
+            write!(f, ";COUNT={}", count)?;
+        }
+        match self.until {
+            Some(Until::Date(date)) => write!(f, ";UNTIL={}", date.format("%Y%m%d"))?,
+            Some(Until::Local(local)) => write!(f, ";UNTIL={}", local.format("%Y%m%dT%H%M%S"))?,
+            Some(Until::Utc(utc)) => write!(f, ";UNTIL={}", utc.format("%Y%m%dT%H%M%SZ"))?,
+            None => {}
+        }
+        let join = |values: Vec<String>| values.join(",");
+        if !self.by_month.is_empty() {
+            let months = self.by_month.iter().map(|m| m.to_string()).collect();
+            write!(f, ";BYMONTH={}", join(months))?;
+        }
+        if !self.by_month_day.is_empty() {
+            let days = self.by_month_day.iter().map(|d| d.to_string()).collect();
+            write!(f, ";BYMONTHDAY={}", join(days))?;
+        }
+        if !self.by_day.is_empty() {
+            let days = self
+                .by_day
+                .iter()
+                .map(|day| match day.ordinal {
+                    Some(n) => format!("{}{}", n, weekday_code(day.weekday)),
+                    None => weekday_code(day.weekday).to_string(),
+                })
+                .collect();
+            write!(f, ";BYDAY={}", join(days))?;
+        }
+        Ok(())
+    }
+}
+
+fn ordinal_text(n: i32) -> String {
+    let suffix = match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{}{}", n, suffix)
+}
+
+impl RecurrenceRule {
+    fn validate(&self) -> Result<()> {
+        if self.interval == 0 || self.interval > MAX_INTERVAL {
+            return Err(rule_error(format!(
+                "INTERVAL must be between 1 and {}",
+                MAX_INTERVAL
+            )));
+        }
+        if self.count.is_some() && self.until.is_some() {
+            return Err(rule_error("A rule cannot have both COUNT and UNTIL"));
+        }
+        if self.count.is_some_and(|n| n == 0 || n > MAX_COUNT) {
+            return Err(rule_error(format!(
+                "COUNT must be between 1 and {}",
+                MAX_COUNT
+            )));
+        }
+        if self.by_month.iter().any(|m| !(1..=12).contains(m)) {
+            return Err(rule_error("BYMONTH must be between 1 and 12"));
+        }
+        if self
+            .by_month_day
+            .iter()
+            .any(|d| *d == 0 || !(-31..=31).contains(d))
+        {
+            return Err(rule_error(
+                "BYMONTHDAY must be between 1 and 31, or -31 and -1",
+            ));
+        }
+        if !self.by_month_day.is_empty() && self.frequency == Frequency::Weekly {
+            return Err(rule_error("BYMONTHDAY cannot be used with FREQ=WEEKLY"));
+        }
+        for day in &self.by_day {
+            let Some(n) = day.ordinal else { continue };
+            if !matches!(self.frequency, Frequency::Monthly | Frequency::Yearly) {
+                return Err(rule_error(
+                    "Numbered BYDAY entries need FREQ=MONTHLY or FREQ=YEARLY",
+                ));
+            }
+            if n == 0 || !(-5..=5).contains(&n) {
+                return Err(rule_error(
+                    "BYDAY numbers must be between 1 and 5, or -5 and -1",
+                ));
+            }
+            if !self.by_month_day.is_empty() {
+                return Err(rule_error(
+                    "Numbered BYDAY entries cannot be combined with BYMONTHDAY",
+                ));
+            }
+        }
+        if self.frequency == Frequency::Yearly
+            && !self.by_day.is_empty()
+            && self.by_month.is_empty()
+        {
+            return Err(rule_error("Yearly BYDAY rules need a BYMONTH"));
+        }
+        Ok(())
+    }
+
+    /// Whether the series ends
+    pub fn is_finite(&self) -> bool {
+        self.count.is_some() || self.until.is_some()
+    }
+
+    /// Write `UNTIL` as RFC 5545 requires alongside a zoned start: a date
+    /// for all-day events and a UTC time otherwise. A date-only end of a
+    /// timed series includes that whole local day.
+    pub fn normalize(&mut self, timezone: Tz, all_day: bool) {
+        self.until = self.until.map(|until| match (until, all_day) {
+            (Until::Date(date), true) => Until::Date(date),
+            (Until::Local(local), true) => Until::Date(local.date()),
+            (Until::Utc(utc), true) => Until::Date(utc.with_timezone(&timezone).date_naive()),
+            (Until::Date(date), false) => Until::Utc(
+                local_to_utc(timezone, date.and_time(NaiveTime::MIN) + Duration::days(1))
+                    - Duration::seconds(1),
+            ),
+            (Until::Local(local), false) => Until::Utc(local_to_utc(timezone, local)),
+            (Until::Utc(utc), false) => Until::Utc(utc),
+        });
+    }
+
+    /// English summary, such as "Every 2 weeks on Monday, Thursday, 10 times"
+    pub fn describe(&self) -> String {
+        let (unit, units) = match self.frequency {
+            Frequency::Daily => ("day", "days"),
+            Frequency::Weekly => ("week", "weeks"),
+            Frequency::Monthly => ("month", "months"),
+            Frequency::Yearly => ("year", "years"),
+        };
+        let mut text = if self.interval == 1 {
+            format!("Every {}", unit)
+        } else {
+            format!("Every {} {}", self.interval, units)
+        };
+        if !self.by_month.is_empty() {
+            let months: Vec<_> = self
+                .by_month
+                .iter()
+                .filter_map(|m| Month::try_from(*m as u8).ok())
+                .map(|m| m.name())
+                .collect();
+            text.push_str(&format!(" in {}", months.join(", ")));
+        }
+        if !self.by_month_day.is_empty() {
+            let days: Vec<_> = self
+                .by_month_day
+                .iter()
+                .map(|d| match d {
+                    -1 => "the last day".to_string(),
+                    d if *d < 0 => format!("{} to last day", ordinal_text(-d)),
+                    d => format!("day {}", d),
+                })
+                .collect();
+            text.push_str(&format!(" on {}", days.join(", ")));
+        }
+        if !self.by_day.is_empty() {
+            let days: Vec<_> = self
+                .by_day
+                .iter()
+                .map(|day| {
+                    let name = weekday_name(day.weekday);
+                    match day.ordinal {
+                        None => name.to_string(),
+                        Some(-1) => format!("the last {}", name),
+                        Some(n) if n < 0 => format!("the {} to last {}", ordinal_text(-n), name),
+                        Some(n) => format!("the {} {}", ordinal_text(n), name),
+                    }
+                })
+                .collect();
+            text.push_str(&format!(" on {}", days.join(", ")));
+        }
+        match (self.count, self.until) {
+            (Some(1), _) => text.push_str(", once"),
+            (Some(n), _) => text.push_str(&format!(", {} times", n)),
+            (_, Some(Until::Date(date))) => text.push_str(&format!(", until {}", date)),
+            (_, Some(Until::Local(local))) => text.push_str(&format!(", until {}", local.date())),
+            (_, Some(Until::Utc(utc))) => text.push_str(&format!(", until {}", utc.date_naive())),
+            _ => {}
+        }
+        text
+    }
+
+    /// Local start times of the series beginning at `start`, `start` first
+    pub fn starts(&self, start: NaiveDateTime, timezone: Tz) -> RecurrenceIter<'_> {
+        RecurrenceIter {
+            rule: self,
+            start,
+            timezone,
+            period: 0,
+            pending: VecDeque::from([start]),
+            emitted: 0,
+            done: false,
+        }
+    }
+
+    fn month_days(&self, year: i32, month: u32, default_day: u32) -> Vec<NaiveDate> {
+        let Some(first) = NaiveDate::from_ymd_opt(year, month, 1) else {
+            return Vec::new();
+        };
+        let last = days_in_month(year, month);
+        let mut days: Vec<NaiveDate> = if !self.by_month_day.is_empty() {
+            self.by_month_day
+                .iter()
+                .map(|d| if *d > 0 { *d } else { last as i32 + 1 + d })
+                .filter(|d| (1..=last as i32).contains(d))
+                .filter_map(|d| first.with_day(d as u32))
+                .collect()
+        } else if !self.by_day.is_empty() {
+            self.by_day
+                .iter()
+                .flat_map(|day| weekdays_in_month(first, last, *day))
+                .collect()
+        } else {
+            first.with_day(default_day).into_iter().collect()
+        };
+        if !self.by_month_day.is_empty() && !self.by_day.is_empty() {
+            days.retain(|date| self.by_day.iter().any(|day| day.weekday == date.weekday()));
+        }
+        days
+    }
+
+    /// Candidate dates of one period; `None` once dates run out of range
+    fn period_dates(&self, start: NaiveDate, period: u32) -> Option<Vec<NaiveDate>> {
+        let step = i64::from(period) * i64::from(self.interval);
+        let dates = match self.frequency {
+            Frequency::Daily => {
+                let day = start.checked_add_signed(Duration::days(step))?;
+                let month_day_matches = self.by_month_day.is_empty()
+                    || self.by_month_day.iter().any(|d| {
+                        let last = days_in_month(day.year(), day.month()) as i32;
+                        *d == day.day() as i32 || last + 1 + d == day.day() as i32
+                    });
+                let weekday_matches = self.by_day.is_empty()
+                    || self.by_day.iter().any(|d| d.weekday == day.weekday());
+                let month_matches =
+                    self.by_month.is_empty() || self.by_month.contains(&day.month());
+                if month_day_matches && weekday_matches && month_matches {
+                    vec![day]
+                } else {
+                    Vec::new()
+                }
+            }
+            Frequency::Weekly => {
+                let monday =
+                    start - Duration::days(i64::from(start.weekday().num_days_from_monday()));
+                let monday = monday.checked_add_signed(Duration::weeks(step))?;
+                let weekdays: Vec<Weekday> = if self.by_day.is_empty() {
+                    vec![start.weekday()]
+                } else {
+                    self.by_day.iter().map(|d| d.weekday).collect()
+                };
+                weekdays
+                    .into_iter()
+                    .map(|w| monday + Duration::days(i64::from(w.num_days_from_monday())))
+                    .filter(|d| self.by_month.is_empty() || self.by_month.contains(&d.month()))
+                    .collect()
+            }
+            Frequency::Monthly => {
+                let months = i64::from(start.year()) * 12 + i64::from(start.month0()) + step;
+                let year = i32::try_from(months.div_euclid(12)).ok()?;
+                let month = months.rem_euclid(12) as u32 + 1;
+                NaiveDate::from_ymd_opt(year, month, 1)?;
+                if !self.by_month.is_empty() && !self.by_month.contains(&month) {
+                    Vec::new()
+                } else {
+                    self.month_days(year, month, start.day())
+                }
+            }
+            Frequency::Yearly => {
+                let year = i32::try_from(i64::from(start.year()) + step).ok()?;
+                NaiveDate::from_ymd_opt(year, 1, 1)?;
+                let months = if self.by_month.is_empty() {
+                    vec![start.month()]
+                } else {
+                    self.by_month.clone()
+                };
+                months
+                    .into_iter()
+                    .flat_map(|month| self.month_days(year, month, start.day()))
+                    .collect()
+            }
+        };
+        Some(dates)
+    }
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    let (next_year, next_month) = if month == 12 {
+        (year + 1, 1)
+    } else {
+        (year, month + 1)
+    };
+    NaiveDate::from_ymd_opt(next_year, next_month, 1)
+        .and_then(|next| next.pred_opt())
+        .map(|last| last.day())
+        .unwrap_or(28)
+}
+
+fn weekdays_in_month(first: NaiveDate, last: u32, day: ByDay) -> Vec<NaiveDate> {
+    let offset =
+        (7 + day.weekday.num_days_from_monday() - first.weekday().num_days_from_monday()) % 7;
+    let all: Vec<NaiveDate> = (1 + offset..=last)
+        .step_by(7)
+        .filter_map(|d| first.with_day(d))
+        .collect();
+    match day.ordinal {
+        None => all,
+        Some(n) if n > 0 => all.get(n as usize - 1).copied().into_iter().collect(),
+        Some(n) => all
+            .len()
+            .checked_sub(n.unsigned_abs() as usize)
+            .and_then(|i| all.get(i).copied())
+            .into_iter()
+            .collect(),
+    }
+}
+
+/// UTC instant of a local time. Times skipped by a daylight saving change
+/// move an hour forward; repeated times take the first instant.
+pub fn local_to_utc(timezone: Tz, local: NaiveDateTime) -> DateTime<Utc> {
+    timezone
+        .from_local_datetime(&local)
+        .earliest()
+        .or_else(|| {
+            timezone
+                .from_local_datetime(&(local + Duration::hours(1)))
+                .earliest()
+        })
+        .map(|time| time.with_timezone(&Utc))
+        .unwrap_or_else(|| local.and_utc())
+}
+
+/// Local start times of a recurring series; see [`RecurrenceRule::starts`]
+pub struct RecurrenceIter<'a> {
+    rule: &'a RecurrenceRule,
+    start: NaiveDateTime,
+    timezone: Tz,
+    period: u32,
+    pending: VecDeque<NaiveDateTime>,
+    emitted: u32,
+    done: bool,
+}
+
+impl RecurrenceIter<'_> {
+    fn past_until(&self, local: NaiveDateTime) -> bool {
+        match self.rule.until {
+            Some(Until::Date(date)) => local.date() > date,
+            Some(Until::Local(until)) => local > until,
+            Some(Until::Utc(until)) => local_to_utc(self.timezone, local) > until,
+            None => false,
+        }
+    }
+}
+
+impl Iterator for RecurrenceIter<'_> {
+    type Item = NaiveDateTime;
+
+    fn next(&mut self) -> Option<NaiveDateTime> {
+        while !self.done {
+            if let Some(local) = self.pending.pop_front() {
+                // DTSTART is the first occurrence even when the rule would
+                // not produce it, and counts towards COUNT
+                if (self.emitted > 0 && self.past_until(local))
+                    || self.rule.count.is_some_and(|count| self.emitted >= count)
+                {
+                    self.done = true;
+                    return None;
+                }
+                self.emitted += 1;
+                return Some(local);
+            }
+            if self.period >= MAX_PERIODS {
+                self.done = true;
+                return None;
+            }
+            let Some(dates) = self.rule.period_dates(self.start.date(), self.period) else {
+                self.done = true;
+                return None;
+            };
+            self.period += 1;
+            let mut starts: Vec<NaiveDateTime> = dates
+                .into_iter()
+                .map(|date| date.and_time(self.start.time()))
+                .filter(|local| *local > self.start)
+                .collect();
+            starts.sort();
+            starts.dedup();
+            self.pending.extend(starts);
+        }
+        None
+    }
+}
+
+/// Whether an event takes place, schema.org's `eventStatus`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventStatus {
+    #[default]
+    Scheduled,
+    Cancelled,
+    Postponed,
+    Rescheduled,
+    MovedOnline,
+}
+
+impl EventStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Scheduled => "scheduled",
+            Self::Cancelled => "cancelled",
+            Self::Postponed => "postponed",
+            Self::Rescheduled => "rescheduled",
+            Self::MovedOnline => "moved_online",
+        }
+    }
+
+    /// Status stored in the database; unknown values read as scheduled
+    pub fn parse(status: &str) -> Self {
+        match status {
+            "cancelled" => Self::Cancelled,
+            "postponed" => Self::Postponed,
+            "rescheduled" => Self::Rescheduled,
+            "moved_online" => Self::MovedOnline,
+            _ => Self::Scheduled,
+        }
+    }
+
+    pub fn schema_org(&self) -> &'static str {
+        match self {
+            Self::Scheduled => "https://schema.org/EventScheduled",
+            Self::Cancelled => "https://schema.org/EventCancelled",
+            Self::Postponed => "https://schema.org/EventPostponed",
+            Self::Rescheduled => "https://schema.org/EventRescheduled",
+            Self::MovedOnline => "https://schema.org/EventMovedOnline",
+        }
+    }
+
+    /// iCalendar `STATUS`
+    pub fn ical(&self) -> &'static str {
+        match self {
+            Self::Cancelled => "CANCELLED",
+            Self::Postponed => "TENTATIVE",
+            _ => "CONFIRMED",
+        }
+    }
+}
+
+/// Where attendees take part
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AttendanceMode {
+    Offline,
+    Online,
+    Mixed,
+}
+
+impl AttendanceMode {
+    fn of(venue: Option<&Venue>, online_url: Option<&str>, status: EventStatus) -> Self {
+        match (venue.is_some(), online_url.is_some()) {
+            _ if status == EventStatus::MovedOnline => Self::Online,
+            (true, true) => Self::Mixed,
+            (false, true) => Self::Online,
+            _ => Self::Offline,
+        }
+    }
+
+    pub fn schema_org(&self) -> &'static str {
+        match self {
+            Self::Offline => "https://schema.org/OfflineEventAttendanceMode",
+            Self::Online => "https://schema.org/OnlineEventAttendanceMode",
+            Self::Mixed => "https://schema.org/MixedEventAttendanceMode",
+        }
+    }
+}
+
+/// Place an event is held at
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Venue {
+    pub name: String,
+    pub street_address: Option<String>,
+    pub locality: Option<String>,
+    pub region: Option<String>,
+    pub postal_code: Option<String>,
+    /// ISO 3166-1 country code
+    pub country: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub url: Option<String>,
+}
+
+impl Venue {
+    fn validate(&self) -> Result<()> {
+        if self.name.trim().is_empty() || self.name.len() > MAX_NAME_LENGTH {
+            return Err(Error::invalid_input(
+                "venue.name",
+                format!("Venues need a name of at most {} bytes", MAX_NAME_LENGTH),
+            ));
+        }
+        match (self.latitude, self.longitude) {
+            (None, None) => {}
+            (Some(lat), Some(lon))
+                if (-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon) => {}
+            _ => {
+                return Err(Error::invalid_input(
+                    "venue.latitude",
+                    "Give both latitude (-90 to 90) and longitude (-180 to 180)",
+                ));
+            }
+        }
+        if self.url.as_deref().is_some_and(|url| !is_web_url(url)) {
+            return Err(Error::invalid_input(
+                "venue.url",
+                "The venue URL must be an http(s) URL",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Postal address on one line
+    pub fn address(&self) -> String {
+        [
+            &self.street_address,
+            &self.locality,
+            &self.region,
+            &self.postal_code,
+            &self.country,
+        ]
+        .into_iter()
+        .flatten()
+        .map(|part| part.trim())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(", ")
+    }
+
+    /// Name and address, as calendar clients show the location
+    pub fn location(&self) -> String {
+        match self.address() {
+            address if address.is_empty() => self.name.clone(),
+            address => format!("{}, {}", self.name, address),
+        }
+    }
+}
+
+/// One occurrence of an event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Occurrence {
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+}
+
+/// When an event takes place: its first occurrence in local time, and the
+/// rule repeating it
+#[derive(Debug, Clone)]
+pub struct Schedule {
+    pub timezone: Tz,
+    pub start: NaiveDateTime,
+    /// Exclusive; midnight after the last day for all-day events
+    pub end: NaiveDateTime,
+    pub all_day: bool,
+    pub rule: Option<RecurrenceRule>,
+    /// Local start times of cancelled occurrences
+    pub exdates: Vec<NaiveDateTime>,
+}
+
+impl Schedule {
+    fn local_starts(&self) -> Box<dyn Iterator<Item = NaiveDateTime> + '_> {
+        let starts: Box<dyn Iterator<Item = NaiveDateTime>> = match &self.rule {
+            Some(rule) => Box::new(rule.starts(self.start, self.timezone)),
+            None => Box::new(std::iter::once(self.start)),
+        };
+        Box::new(starts.filter(|local| !self.exdates.contains(local)))
+    }
+
+    fn occurrence(&self, local_start: NaiveDateTime) -> Occurrence {
+        Occurrence {
+            starts_at: local_to_utc(self.timezone, local_start),
+            ends_at: local_to_utc(self.timezone, local_start + (self.end - self.start)),
+        }
+    }
+
+    /// Occurrences overlapping `from..to`, at most `limit`
+    pub fn occurrences(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        limit: usize,
+    ) -> Vec<Occurrence> {
+        self.local_starts()
+            .map(|local| self.occurrence(local))
+            .take_while(|occurrence| occurrence.starts_at < to)
+            .filter(|occurrence| occurrence.ends_at > from)
+            .take(limit)
+            .collect()
+    }
+
+    /// First occurrence that has not ended by `now`
+    pub fn next_occurrence(&self, now: DateTime<Utc>) -> Option<Occurrence> {
+        self.occurrences(now, DateTime::<Utc>::MAX_UTC, 1).pop()
+    }
+
+    /// End of the last occurrence, or `None` when the series never ends
+    pub fn series_end(&self) -> Option<DateTime<Utc>> {
+        if self.rule.as_ref().is_some_and(|rule| !rule.is_finite()) {
+            return None;
+        }
+        let last = self.local_starts().last().unwrap_or(self.start);
+        Some(self.occurrence(last).ends_at)
+    }
+
+    /// Occurrence times in the event's timezone, as templates show them
+    pub fn format(&self, occurrence: &Occurrence) -> OccurrenceData {
+        let local = |time: DateTime<Utc>| time.with_timezone(&self.timezone);
+        if self.all_day {
+            // All-day events end at the midnight after their last day;
+            // people read the last day itself
+            let last_day = local(occurrence.ends_at).date_naive().pred_opt();
+            let start = local(occurrence.starts_at).date_naive();
+            OccurrenceData {
+                start: start.to_string(),
+                end: last_day.unwrap_or(start).max(start).to_string(),
+            }
+        } else {
+            OccurrenceData {
+                start: local(occurrence.starts_at).to_rfc3339(),
+                end: local(occurrence.ends_at).to_rfc3339(),
+            }
+        }
+    }
+}
+
+/// An event's schedule, venue and tickets
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct Event {
+    pub post_id: Uuid,
+    /// Start and end of the first occurrence
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub all_day: bool,
+    pub timezone: String,
+    pub rrule: Option<String>,
+    pub exdates: Json<Vec<NaiveDateTime>>,
+    /// End of the last occurrence; `None` when the series never ends
+    pub series_ends_at: Option<DateTime<Utc>>,
+    pub venue: Option<Json<Venue>>,
+    pub online_url: Option<String>,
+    pub status: String,
+    pub organizer_name: Option<String>,
+    pub organizer_url: Option<String>,
+    pub ticket_url: Option<String>,
+    pub price: Option<String>,
+    /// ISO 4217 currency of `price`
+    pub currency: Option<String>,
+    pub updated_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+const EVENT_COLUMNS: &str = "post_id, starts_at, ends_at, all_day, timezone, rrule, exdates, \
+     series_ends_at, venue, online_url, status, organizer_name, organizer_url, ticket_url, \
+     price, currency, updated_by, created_at, updated_at";
+
+fn prefixed_columns(prefix: &str) -> String {
+    EVENT_COLUMNS
+        .split(", ")
+        .map(|column| format!("{}.{}", prefix, column.trim()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl Event {
+    /// Event details of a post, if it is one
+    pub async fn load(pool: &PgPool, post_id: Uuid) -> Result<Option<Self>> {
+        sqlx::query_as(&format!(
+            "SELECT {} FROM event_details WHERE post_id = $1 \
+             AND post_id IN (SELECT id FROM posts WHERE {})",
+            EVENT_COLUMNS,
+            site_filter("site_id")
+        ))
+        .bind(post_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load event", e))
+    }
+
+    /// Events with an occurrence that may overlap `from..to` (open-ended
+    /// without `to`), with their posts
+    pub async fn overlapping(
+        pool: &PgPool,
+        from: DateTime<Utc>,
+        to: Option<DateTime<Utc>>,
+        published_only: bool,
+    ) -> Result<Vec<EventSummary>> {
+        sqlx::query_as(&format!(
+            "SELECT {}, p.title, p.slug, p.status AS post_status, p.published_at \
+             FROM event_details e JOIN posts p ON p.id = e.post_id \
+             WHERE p.deleted_at IS NULL AND p.post_type = $4 AND {} \
+             AND ($3 = FALSE OR p.status = 'published') \
+             AND ($2::timestamptz IS NULL OR e.starts_at < $2) \
+             AND (e.series_ends_at IS NULL OR e.series_ends_at > $1) \
+             ORDER BY e.starts_at",
+            prefixed_columns("e"),
+            site_filter("p.site_id")
+        ))
+        .bind(from)
+        .bind(to)
+        .bind(published_only)
+        .bind(EVENT_POST_TYPE)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load events", e))
+    }
+
+    /// Published events whose last occurrence ended before `before`, most
+    /// recent first
+    pub async fn ended(
+        pool: &PgPool,
+        before: DateTime<Utc>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<EventSummary>, i64)> {
+        let events = sqlx::query_as(&format!(
+            "SELECT {}, p.title, p.slug, p.status AS post_status, p.published_at \
+             FROM event_details e JOIN posts p ON p.id = e.post_id \
+             WHERE p.deleted_at IS NULL AND p.post_type = $2 AND p.status = 'published' \
+             AND e.series_ends_at <= $1 AND {} \
+             ORDER BY e.series_ends_at DESC LIMIT $3 OFFSET $4",
+            prefixed_columns("e"),
+            site_filter("p.site_id")
+        ))
+        .bind(before)
+        .bind(EVENT_POST_TYPE)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load past events", e))?;
+
+        let total: i64 = sqlx::query_scalar(&format!(
+            "SELECT COUNT(*) FROM event_details e JOIN posts p ON p.id = e.post_id \
+             WHERE p.deleted_at IS NULL AND p.post_type = $2 AND p.status = 'published' \
+             AND e.series_ends_at <= $1 AND {}",
+            site_filter("p.site_id")
+        ))
+        .bind(before)
+        .bind(EVENT_POST_TYPE)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to count past events", e))?;
+        Ok((events, total))
+    }
+
+    pub fn status(&self) -> EventStatus {
+        EventStatus::parse(&self.status)
+    }
+
+    fn time_zone(&self) -> Tz {
+        self.timezone.parse().unwrap_or(Tz::UTC)
+    }
+
+    /// The event's schedule; a stored rule that no longer parses is
+    /// treated as a one-off event
+    pub fn schedule(&self) -> Schedule {
+        let timezone = self.time_zone();
+        let local = |time: DateTime<Utc>| time.with_timezone(&timezone).naive_local();
+        Schedule {
+            timezone,
+            start: local(self.starts_at),
+            end: local(self.ends_at),
+            all_day: self.all_day,
+            rule: self.rrule.as_deref().and_then(|rule| rule.parse().ok()),
+            exdates: self.exdates.0.clone(),
+        }
+    }
+}
+
+/// An event with its post, for listings
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct EventSummary {
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    pub event: Event,
+    pub title: String,
+    pub slug: String,
+    pub post_status: String,
+    pub published_at: Option<DateTime<Utc>>,
+}
+
+/// Start and end of an occurrence in the event's timezone: RFC 3339 times,
+/// or the first and last day of all-day events
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OccurrenceData {
+    pub start: String,
+    pub end: String,
+}
+
+/// An event as templates see it, under `post.event`
+#[derive(Debug, Clone, Serialize)]
+pub struct EventData {
+    /// First occurrence
+    pub start: String,
+    pub end: String,
+    pub timezone: String,
+    pub all_day: bool,
+    /// Recurrence rule, as RFC 5545 `RRULE`
+    pub recurrence: Option<String>,
+    /// The rule in words, such as "Every week on Tuesday"
+    pub recurrence_text: Option<String>,
+    /// Next occurrence that has not ended; `None` once the event is over
+    pub next: Option<OccurrenceData>,
+    /// Next few occurrences of recurring events
+    pub upcoming: Vec<OccurrenceData>,
+    pub venue: Option<Venue>,
+    pub online_url: Option<String>,
+    pub attendance_mode: AttendanceMode,
+    pub status: EventStatus,
+    pub organizer_name: Option<String>,
+    pub organizer_url: Option<String>,
+    pub ticket_url: Option<String>,
+    pub price: Option<String>,
+    pub currency: Option<String>,
+}
+
+impl EventData {
+    pub fn new(event: &Event, now: DateTime<Utc>) -> Self {
+        let schedule = event.schedule();
+        let first = Occurrence {
+            starts_at: event.starts_at,
+            ends_at: event.ends_at,
+        };
+        let first = schedule.format(&first);
+        let upcoming: Vec<_> = if schedule.rule.is_some() {
+            schedule
+                .occurrences(now, DateTime::<Utc>::MAX_UTC, UPCOMING_OCCURRENCES)
+                .iter()
+                .map(|occurrence| schedule.format(occurrence))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let next = match schedule.rule {
+            Some(_) => upcoming.first().cloned(),
+            None => schedule
+                .next_occurrence(now)
+                .map(|occurrence| schedule.format(&occurrence)),
+        };
+        let venue = event.venue.as_ref().map(|venue| venue.0.clone());
+        let status = event.status();
+        Self {
+            start: first.start,
+            end: first.end,
+            timezone: event.timezone.clone(),
+            all_day: event.all_day,
+            recurrence: schedule.rule.as_ref().map(|rule| rule.to_string()),
+            recurrence_text: schedule.rule.as_ref().map(|rule| rule.describe()),
+            next,
+            upcoming,
+            attendance_mode: AttendanceMode::of(
+                venue.as_ref(),
+                event.online_url.as_deref(),
+                status,
+            ),
+            venue,
+            online_url: event.online_url.clone(),
+            status,
+            organizer_name: event.organizer_name.clone(),
+            organizer_url: event.organizer_url.clone(),
+            ticket_url: event.ticket_url.clone(),
+            price: event.price.clone(),
+            currency: event.currency.clone(),
+        }
+    }
+}
+
+/// schema.org `Event` for an event page, describing its next occurrence
+/// (or its first once the series is over)
+pub fn event_json_ld(post: &PostData, event: &EventData, url: &str) -> Value {
+    let dates = event.next.clone().unwrap_or_else(|| OccurrenceData {
+        start: event.start.clone(),
+        end: event.end.clone(),
+    });
+
+    let mut locations = Vec::new();
+    if let Some(venue) = &event.venue {
+        let mut address = json!({ "@type": "PostalAddress" });
+        for (key, value) in [
+            ("streetAddress", &venue.street_address),
+            ("addressLocality", &venue.locality),
+            ("addressRegion", &venue.region),
+            ("postalCode", &venue.postal_code),
+            ("addressCountry", &venue.country),
+        ] {
+            if let Some(value) = value {
+                address[key] = json!(value);
+            }
+        }
+        let mut place = json!({
+            "@type": "Place",
+            "name": venue.name,
+            "address": address,
+        });
+        if let (Some(latitude), Some(longitude)) = (venue.latitude, venue.longitude) {
+            place["geo"] = json!({
+                "@type": "GeoCoordinates",
+                "latitude": latitude,
+                "longitude": longitude,
+            });
+        }
+        if let Some(venue_url) = &venue.url {
+            place["url"] = json!(venue_url);
+        }
+        locations.push(place);
+    }
+    if let Some(online_url) = &event.online_url {
+        locations.push(json!({ "@type": "VirtualLocation", "url": online_url }));
+    }
+
+    let mut data = json!({
+        "@context": "https://schema.org",
+        "@type": "Event",
+        "name": post.title,
+        "url": url,
+        "startDate": dates.start,
+        "endDate": dates.end,
+        "eventStatus": event.status.schema_org(),
+        "eventAttendanceMode": event.attendance_mode.schema_org(),
+    });
+    match locations.len() {
+        0 => {}
+        1 => data["location"] = locations.remove(0),
+        _ => data["location"] = Value::Array(locations),
+    }
+    if let Some(excerpt) = post.excerpt.as_deref().filter(|e| !e.is_empty()) {
+        data["description"] = json!(excerpt);
+    }
+    if let Some(image) = &post.featured_image {
+        data["image"] = json!(image.url);
+    }
+    if let Some(name) = &event.organizer_name {
+        let mut organizer = json!({ "@type": "Organization", "name": name });
+        if let Some(organizer_url) = &event.organizer_url {
+            organizer["url"] = json!(organizer_url);
+        }
+        data["organizer"] = organizer;
+    }
+    if event.ticket_url.is_some() || event.price.is_some() {
+        let mut offer = json!({
+            "@type": "Offer",
+            "url": event.ticket_url.as_deref().unwrap_or(url),
+        });
+        if let Some(price) = &event.price {
+            offer["price"] = json!(price);
+        }
+        if let Some(currency) = &event.currency {
+            offer["priceCurrency"] = json!(currency);
+        }
+        data["offers"] = offer;
+    }
+    data
+}
+
+/// An event in the iCalendar feed
+pub struct CalendarEntry<'a> {
+    pub post: &'a PostData,
+    pub event: &'a Event,
+    /// Absolute URL of the event page
+    pub url: String,
+}
+
+/// Escape a TEXT value (RFC 5545 §3.3.11)
+fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Append a content line, folded at 75 octets (RFC 5545 §3.1)
+fn push_line(out: &mut String, line: &str) {
+    let mut rest = line;
+    let mut limit = 75;
+    while rest.len() > limit {
+        let mut split = limit;
+        while !rest.is_char_boundary(split) {
+            split -= 1;
+        }
+        out.push_str(&rest[..split]);
+        out.push_str("\r\n ");
+        rest = &rest[split..];
+        // Continuation lines start with a space
+        limit = 74;
+    }
+    out.push_str(rest);
+    out.push_str("\r\n");
+}
+
+/// `DTSTART`-style property for a local time of a schedule
+fn ical_time(name: &str, schedule: &Schedule, local: NaiveDateTime) -> String {
+    if schedule.all_day {
+        format!("{};VALUE=DATE:{}", name, local.format("%Y%m%d"))
+    } else if schedule.timezone == Tz::UTC {
+        format!("{}:{}Z", name, local.format("%Y%m%dT%H%M%S"))
+    } else {
+        format!(
+            "{};TZID={}:{}",
+            name,
+            schedule.timezone.name(),
+            local.format("%Y%m%dT%H%M%S")
+        )
+    }
+}
+
+/// Render events as an iCalendar (RFC 5545) document. Zoned times name
+/// their IANA timezone, which calendar clients resolve themselves.
+pub fn render_ical(calendar_name: &str, host: &str, entries: &[CalendarEntry<'_>]) -> String {
+    let mut out = String::new();
+    push_line(&mut out, "BEGIN:VCALENDAR");
+    push_line(&mut out, "VERSION:2.0");
+    push_line(&mut out, "PRODID:-//RustPress//Events//EN");
+    push_line(&mut out, "CALSCALE:GREGORIAN");
+    push_line(&mut out, "METHOD:PUBLISH");
+    push_line(
+        &mut out,
+        &format!("X-WR-CALNAME:{}", escape_text(calendar_name)),
+    );
+
+    for entry in entries {
+        let event = entry.event;
+        let schedule = event.schedule();
+        let stamp = entry.post.updated_at.format("%Y%m%dT%H%M%SZ");
+
+        push_line(&mut out, "BEGIN:VEVENT");
+        push_line(&mut out, &format!("UID:{}@{}", event.post_id, host));
+        push_line(&mut out, &format!("DTSTAMP:{}", stamp));
+        push_line(&mut out, &format!("LAST-MODIFIED:{}", stamp));
+        push_line(&mut out, &ical_time("DTSTART", &schedule, schedule.start));
+        push_line(&mut out, &ical_time("DTEND", &schedule, schedule.end));
+        if let Some(rule) = &schedule.rule {
+            push_line(&mut out, &format!("RRULE:{}", rule));
+        }
+        for exdate in &schedule.exdates {
+            push_line(&mut out, &ical_time("EXDATE", &schedule, *exdate));
+        }
+        push_line(
+            &mut out,
+            &format!("SUMMARY:{}", escape_text(&entry.post.title)),
+        );
+        if let Some(excerpt) = entry.post.excerpt.as_deref().filter(|e| !e.is_empty()) {
+            push_line(&mut out, &format!("DESCRIPTION:{}", escape_text(excerpt)));
+        }
+        let location = event
+            .venue
+            .as_ref()
+            .map(|venue| venue.location())
+            .or_else(|| event.online_url.clone());
+        if let Some(location) = location {
+            push_line(&mut out, &format!("LOCATION:{}", escape_text(&location)));
+        }
+        if let Some(venue) = &event.venue {
+            if let (Some(latitude), Some(longitude)) = (venue.latitude, venue.longitude) {
+                push_line(&mut out, &format!("GEO:{};{}", latitude, longitude));
+            }
+        }
+        push_line(&mut out, &format!("URL:{}", entry.url));
+        push_line(&mut out, &format!("STATUS:{}", event.status().ical()));
+        push_line(&mut out, "END:VEVENT");
+    }
+
+    push_line(&mut out, "END:VCALENDAR");
+    out
+}
+
+/// Attach or replace a post's schedule. Times are local to `timezone`
+/// (UTC when omitted); all-day events use the dates of `starts_at` and
+/// `ends_at`, both inclusive.
+#[derive(Debug, Clone, Deserialize)]
+pub struct EventInput {
+    pub starts_at: NaiveDateTime,
+    /// Defaults to an hour after the start, or the same day
+    pub ends_at: Option<NaiveDateTime>,
+    #[serde(default)]
+    pub all_day: bool,
+    pub timezone: Option<String>,
+    pub rrule: Option<String>,
+    /// Local start times of cancelled occurrences
+    #[serde(default)]
+    pub exdates: Vec<NaiveDateTime>,
+    pub venue: Option<Venue>,
+    pub online_url: Option<String>,
+    #[serde(default)]
+    pub status: EventStatus,
+    pub organizer_name: Option<String>,
+    pub organizer_url: Option<String>,
+    pub ticket_url: Option<String>,
+    pub price: Option<String>,
+    pub currency: Option<String>,
+}
+
+impl EventInput {
+    /// Check the input and work out its schedule
+    pub fn schedule(&self) -> Result<Schedule> {
+        let timezone: Tz = match self.timezone.as_deref().filter(|tz| !tz.is_empty()) {
+            Some(name) => name.parse().map_err(|_| {
+                Error::invalid_input("timezone", format!("Unknown timezone '{}'", name))
+            })?,
+            None => Tz::UTC,
+        };
+
+        let (start, end) = if self.all_day {
+            let start = self.starts_at.date();
+            let last = self.ends_at.map(|end| end.date()).unwrap_or(start);
+            (
+                start.and_time(NaiveTime::MIN),
+                (last + Duration::days(1)).and_time(NaiveTime::MIN),
+            )
+        } else {
+            (
+                self.starts_at,
+                self.ends_at.unwrap_or(self.starts_at + Duration::hours(1)),
+            )
+        };
+        if end < start {
+            return Err(Error::invalid_input(
+                "ends_at",
+                "An event cannot end before it starts",
+            ));
+        }
+        if end - start > Duration::days(MAX_EVENT_DAYS) {
+            return Err(Error::invalid_input(
+                "ends_at",
+                format!("An event can last at most {} days", MAX_EVENT_DAYS),
+            ));
+        }
+
+        let rule = match self
+            .rrule
+            .as_deref()
+            .map(str::trim)
+            .filter(|r| !r.is_empty())
+        {
+            Some(rule) => {
+                let mut rule: RecurrenceRule = rule.parse()?;
+                rule.normalize(timezone, self.all_day);
+                let ends_first = match rule.until {
+                    Some(Until::Date(date)) => date < start.date(),
+                    Some(Until::Utc(until)) => until < local_to_utc(timezone, start),
+                    _ => false,
+                };
+                if ends_first {
+                    return Err(rule_error("The recurrence ends before the event starts"));
+                }
+                Some(rule)
+            }
+            None => None,
+        };
+        if self.exdates.len() > MAX_EXDATES {
+            return Err(Error::invalid_input(
+                "exdates",
+                format!("An event can exclude at most {} dates", MAX_EXDATES),
+            ));
+        }
+        let mut exdates: Vec<NaiveDateTime> = if rule.is_none() {
+            Vec::new()
+        } else if self.all_day {
+            self.exdates
+                .iter()
+                .map(|date| date.date().and_time(NaiveTime::MIN))
+                .collect()
+        } else {
+            self.exdates.clone()
+        };
+        exdates.sort();
+        exdates.dedup();
+
+        Ok(Schedule {
+            timezone,
+            start,
+            end,
+            all_day: self.all_day,
+            rule,
+            exdates,
+        })
+    }
+
+    fn validate(&self) -> Result<()> {
+        if let Some(venue) = &self.venue {
+            venue.validate()?;
+        }
+        for (field, url) in [
+            ("online_url", &self.online_url),
+            ("organizer_url", &self.organizer_url),
+            ("ticket_url", &self.ticket_url),
+        ] {
+            if url.as_deref().is_some_and(|url| !is_web_url(url)) {
+                return Err(Error::invalid_input(field, "Links must be http(s) URLs"));
+            }
+        }
+        for (field, text) in [
+            ("organizer_name", &self.organizer_name),
+            ("price", &self.price),
+        ] {
+            if text.as_deref().is_some_and(|t| t.len() > MAX_NAME_LENGTH) {
+                return Err(Error::invalid_input(
+                    field,
+                    format!("At most {} bytes", MAX_NAME_LENGTH),
+                ));
+            }
+        }
+        if self
+            .currency
+            .as_deref()
+            .is_some_and(|c| c.len() != 3 || !c.bytes().all(|b| b.is_ascii_uppercase()))
+        {
+            return Err(Error::invalid_input(
+                "currency",
+                "Currencies are three-letter ISO 4217 codes",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Period of a calendar view; from now for a month by default
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CalendarQuery {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+/// An occurrence with its event, for calendar views
+#[derive(Debug, Clone, Serialize)]
+pub struct CalendarOccurrence {
+    pub post_id: Uuid,
+    pub title: String,
+    pub slug: String,
+    pub post_status: String,
+    pub status: EventStatus,
+    pub all_day: bool,
+    #[serde(flatten)]
+    pub occurrence: Occurrence,
+}
+
+/// Events and their occurrences
+pub struct EventService {
+    pool: PgPool,
+}
+
+impl EventService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Event of a post
+    pub async fn event(&self, post_id: Uuid) -> Result<Event> {
+        Event::load(&self.pool, post_id)
+            .await?
+            .ok_or_else(|| Error::not_found("Event", post_id.to_string()))
+    }
+
+    /// Events, latest first
+    pub async fn list(&self, page: u32, per_page: u32) -> Result<(Vec<EventSummary>, u64)> {
+        let events = sqlx::query_as(&format!(
+            "SELECT {}, p.title, p.slug, p.status AS post_status, p.published_at \
+             FROM event_details e JOIN posts p ON p.id = e.post_id \
+             WHERE p.deleted_at IS NULL AND {} \
+             ORDER BY e.starts_at DESC \
+             LIMIT $1 OFFSET $2",
+            prefixed_columns("e"),
+            site_filter("p.site_id")
+        ))
+        .bind(i64::from(per_page))
+        .bind(i64::from(page.saturating_sub(1)) * i64::from(per_page))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to list events", e))?;
+
+        let total: i64 = sqlx::query_scalar(&format!(
+            "SELECT COUNT(*) FROM event_details e JOIN posts p ON p.id = e.post_id \
+             WHERE p.deleted_at IS NULL AND {}",
+            site_filter("p.site_id")
+        ))
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to count events", e))?;
+        Ok((events, total as u64))
+    }
+
+    /// Occurrences of all events, drafts included, in a calendar view
+    pub async fn occurrences(&self, query: &CalendarQuery) -> Result<Vec<CalendarOccurrence>> {
+        let from = query.from.unwrap_or_else(Utc::now);
+        let to = query
+            .to
+            .unwrap_or(from + Duration::days(DEFAULT_CALENDAR_DAYS));
+        if to <= from || to - from > Duration::days(MAX_CALENDAR_DAYS) {
+            return Err(Error::invalid_input(
+                "to",
+                format!(
+                    "A calendar view ends after it starts and spans at most {} days",
+                    MAX_CALENDAR_DAYS
+                ),
+            ));
+        }
+
+        let events = Event::overlapping(&self.pool, from, Some(to), false).await?;
+        let mut occurrences: Vec<CalendarOccurrence> = events
+            .iter()
+            .flat_map(|summary| {
+                summary
+                    .event
+                    .schedule()
+                    .occurrences(from, to, MAX_COUNT as usize)
+                    .into_iter()
+                    .map(|occurrence| CalendarOccurrence {
+                        post_id: summary.event.post_id,
+                        title: summary.title.clone(),
+                        slug: summary.slug.clone(),
+                        post_status: summary.post_status.clone(),
+                        status: summary.event.status(),
+                        all_day: summary.event.all_day,
+                        occurrence,
+                    })
+            })
+            .collect();
+        occurrences.sort_by_key(|o| o.occurrence.starts_at);
+        Ok(occurrences)
+    }
+
+    /// Make a post an event, or replace its schedule
+    pub async fn save(&self, post_id: Uuid, user_id: Uuid, input: EventInput) -> Result<Event> {
+        let schedule = input.schedule()?;
+        input.validate()?;
+
+        let post_type: Option<String> = sqlx::query_scalar(&format!(
+            "SELECT post_type FROM posts WHERE id = $1 AND deleted_at IS NULL AND {}",
+            site_filter("site_id")
+        ))
+        .bind(post_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load post", e))?;
+        match post_type.as_deref() {
+            None => return Err(Error::not_found("Post", post_id.to_string())),
+            Some("post") | Some(EVENT_POST_TYPE) => {}
+            Some(_) => {
+                return Err(Error::invalid_input("post", "Only posts can be events"));
+            }
+        }
+
+        let first = schedule.occurrence(schedule.start);
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| Error::database_with_source("Failed to start transaction", e))?;
+        let event: Event = sqlx::query_as(&format!(
+            "INSERT INTO event_details (post_id, starts_at, ends_at, all_day, timezone, rrule, \
+             exdates, series_ends_at, venue, online_url, status, organizer_name, organizer_url, \
+             ticket_url, price, currency, updated_by) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17) \
+             ON CONFLICT (post_id) DO UPDATE SET starts_at = EXCLUDED.starts_at, \
+             ends_at = EXCLUDED.ends_at, all_day = EXCLUDED.all_day, \
+             timezone = EXCLUDED.timezone, rrule = EXCLUDED.rrule, exdates = EXCLUDED.exdates, \
+             series_ends_at = EXCLUDED.series_ends_at, venue = EXCLUDED.venue, \
+             online_url = EXCLUDED.online_url, status = EXCLUDED.status, \
+             organizer_name = EXCLUDED.organizer_name, organizer_url = EXCLUDED.organizer_url, \
+             ticket_url = EXCLUDED.ticket_url, price = EXCLUDED.price, \
+             currency = EXCLUDED.currency, updated_by = EXCLUDED.updated_by, \
+             updated_at = NOW() \
+             RETURNING {}",
+            EVENT_COLUMNS
+        ))
+        .bind(post_id)
+        .bind(first.starts_at)
+        .bind(first.ends_at)
+        .bind(schedule.all_day)
+        .bind(schedule.timezone.name())
+        .bind(schedule.rule.as_ref().map(|rule| rule.to_string()))
+        .bind(Json(&schedule.exdates))
+        .bind(schedule.series_end())
+        .bind(input.venue.map(Json))
+        .bind(input.online_url)
+        .bind(input.status.as_str())
+        .bind(input.organizer_name)
+        .bind(input.organizer_url)
+        .bind(input.ticket_url)
+        .bind(input.price)
+        .bind(input.currency)
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to save event", e))?;
+
+        sqlx::query("UPDATE posts SET post_type = $2, updated_at = NOW() WHERE id = $1")
+            .bind(post_id)
+            .bind(EVENT_POST_TYPE)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to update post", e))?;
+        tx.commit()
+            .await
+            .map_err(|e| Error::database_with_source("Failed to save event", e))?;
+        Ok(event)
+    }
+
+    /// Turn an event back into a plain post
+    pub async fn remove(&self, post_id: Uuid) -> Result<()> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| Error::database_with_source("Failed to start transaction", e))?;
+        let removed = sqlx::query(&format!(
+            "DELETE FROM event_details WHERE post_id = $1 \
+             AND post_id IN (SELECT id FROM posts WHERE {})",
+            site_filter("site_id")
+        ))
+        .bind(post_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to remove event", e))?
+        .rows_affected();
+        if removed == 0 {
+            return Err(Error::not_found("Event", post_id.to_string()));
+        }
+        sqlx::query(
+            "UPDATE posts SET post_type = 'post', updated_at = NOW() \
+             WHERE id = $1 AND post_type = $2",
+        )
+        .bind(post_id)
+        .bind(EVENT_POST_TYPE)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to update post", e))?;
+        tx.commit()
+            .await
+            .map_err(|e| Error::database_with_source("Failed to remove event", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn local(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn starts(rule: &str, start: &str, n: usize) -> Vec<String> {
+        let rule: RecurrenceRule = rule.parse().unwrap();
+        rule.starts(local(start), Tz::UTC)
+            .take(n)
+            .map(|s| s.format("%Y-%m-%d %H:%M").to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_recurrence_rule() {
+        let rule: RecurrenceRule = "RRULE:FREQ=monthly;BYDAY=-1FR;COUNT=6;WKST=MO"
+            .parse()
+            .unwrap();
+        assert_eq!(rule.frequency, Frequency::Monthly);
+        assert_eq!(
+            rule.by_day,
+            vec![ByDay {
+                ordinal: Some(-1),
+                weekday: Weekday::Fri
+            }]
+        );
+        assert_eq!(rule.to_string(), "FREQ=MONTHLY;COUNT=6;BYDAY=-1FR");
+        assert_eq!(rule.describe(), "Every month on the last Friday, 6 times");
+
+        for invalid in [
+            "BYDAY=MO",
+            "FREQ=HOURLY",
+            "FREQ=WEEKLY;BYSETPOS=1",
+            "FREQ=DAILY;COUNT=2;UNTIL=20250101",
+            "FREQ=WEEKLY;BYDAY=2MO",
+            "FREQ=YEARLY;BYDAY=1MO",
+            "FREQ=MONTHLY;BYMONTHDAY=0",
+        ] {
+            assert!(invalid.parse::<RecurrenceRule>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_expand_recurrence_rule() {
+        // DTSTART counts as the first occurrence
+        assert_eq!(
+            starts("FREQ=WEEKLY;BYDAY=TU,TH;COUNT=4", "2025-06-02 19:00", 10),
+            [
+                "2025-06-02 19:00",
+                "2025-06-03 19:00",
+                "2025-06-05 19:00",
+                "2025-06-10 19:00"
+            ]
+        );
+        // Months without a 31st are skipped
+        assert_eq!(
+            starts("FREQ=MONTHLY;BYMONTHDAY=31", "2025-01-31 10:00", 3),
+            ["2025-01-31 10:00", "2025-03-31 10:00", "2025-05-31 10:00"]
+        );
+        assert_eq!(
+            starts("FREQ=MONTHLY;INTERVAL=2;BYDAY=2TU", "2025-01-14 18:00", 3),
+            ["2025-01-14 18:00", "2025-03-11 18:00", "2025-05-13 18:00"]
+        );
+        assert_eq!(
+            starts("FREQ=YEARLY;BYMONTH=11;BYDAY=4TH", "2025-11-27 12:00", 2),
+            ["2025-11-27 12:00", "2026-11-26 12:00"]
+        );
+        assert_eq!(
+            starts("FREQ=DAILY;UNTIL=20250103", "2025-01-01 09:00", 10),
+            ["2025-01-01 09:00", "2025-01-02 09:00", "2025-01-03 09:00"]
+        );
+    }
+
+    #[test]
+    fn test_schedule_keeps_local_time_across_dst() {
+        let timezone: Tz = "Europe/Berlin".parse().unwrap();
+        let schedule = Schedule {
+            timezone,
+            start: local("2025-03-25 19:00"),
+            end: local("2025-03-25 21:00"),
+            all_day: false,
+            rule: Some("FREQ=WEEKLY;COUNT=3".parse().unwrap()),
+            exdates: vec![local("2025-04-08 19:00")],
+        };
+        let occurrences =
+            schedule.occurrences(DateTime::<Utc>::MIN_UTC, DateTime::<Utc>::MAX_UTC, 10);
+        // CET before the change on March 30th, CEST after it
+        assert_eq!(occurrences.len(), 1 + 1);
+        assert_eq!(
+            occurrences[0].starts_at.to_rfc3339(),
+            "2025-03-25T18:00:00+00:00"
+        );
+        assert_eq!(
+            occurrences[1].starts_at.to_rfc3339(),
+            "2025-04-01T17:00:00+00:00"
+        );
+        assert_eq!(
+            schedule.series_end().unwrap().to_rfc3339(),
+            "2025-04-01T19:00:00+00:00"
+        );
+        assert_eq!(
+            schedule.format(&occurrences[1]).start,
+            "2025-04-01T19:00:00+02:00"
+        );
+
+        let infinite = Schedule {
+            rule: Some("FREQ=WEEKLY".parse().unwrap()),
+            ..schedule
+        };
+        assert_eq!(infinite.series_end(), None);
+        let now = "2025-04-09T00:00:00Z".parse().unwrap();
+        assert_eq!(
+            infinite
+                .next_occurrence(now)
+                .unwrap()
+                .starts_at
+                .to_rfc3339(),
+            "2025-04-15T17:00:00+00:00"
+        );
+    }
+
+    #[test]
+    fn test_event_input_schedule() {
+        let input = EventInput {
+            starts_at: local("2025-07-04 00:00"),
+            ends_at: Some(local("2025-07-06 00:00")),
+            all_day: true,
+            timezone: Some("America/New_York".to_string()),
+            rrule: Some("FREQ=YEARLY;UNTIL=20270704T120000Z".to_string()),
+            exdates: Vec::new(),
+            venue: None,
+            online_url: None,
+            status: EventStatus::Scheduled,
+            organizer_name: None,
+            organizer_url: None,
+            ticket_url: None,
+            price: None,
+            currency: None,
+        };
+        let schedule = input.schedule().unwrap();
+        assert_eq!(schedule.end, local("2025-07-07 00:00"));
+        assert_eq!(
+            schedule.rule.as_ref().unwrap().to_string(),
+            "FREQ=YEARLY;UNTIL=20270704"
+        );
+        let first = schedule.occurrence(schedule.start);
+        assert_eq!(
+            schedule.format(&first),
+            OccurrenceData {
+                start: "2025-07-04".to_string(),
+                end: "2025-07-06".to_string()
+            }
+        );
+
+        let bad_zone = EventInput {
+            timezone: Some("Mars/Olympus".to_string()),
+            ..input.clone()
+        };
+        assert!(bad_zone.schedule().is_err());
+        let ends_first = EventInput {
+            rrule: Some("FREQ=DAILY;UNTIL=20250101".to_string()),
+            ..input
+        };
+        assert!(ends_first.schedule().is_err());
+    }
+
+    #[test]
+    fn test_ical_text() {
+        assert_eq!(
+            escape_text("Talks; Q&A, drinks\nBar"),
+            "Talks\\; Q&A\\, drinks\\nBar"
+        );
+
+        let mut out = String::new();
+        push_line(&mut out, &format!("SUMMARY:{}", "é".repeat(60)));
+        let lines: Vec<_> = out.split("\r\n").filter(|l| !l.is_empty()).collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines.iter().all(|l| l.len() <= 75));
+        assert!(lines[1].starts_with(' '));
+        assert_eq!(
+            out.replace("\r\n ", ""),
+            format!("SUMMARY:{}\r\n", "é".repeat(60))
+        );
+    }
+}
@@ -0,0 +1,431 @@
+//! Streaming Export Service
+//!
+//! Streams large datasets (posts, users, comments, analytics rollups and
+//! audit logs) as CSV or JSONL. Rows are read in keyset-paginated chunks and
+//! written to the response as they are fetched, so memory use stays flat
+//! regardless of site size. Exports are ordered by `id` and every row
+//! carries it, so an interrupted download can be resumed by passing the
+//! last exported id as `after`.
+
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use rustpress_core::error::{Error, Result};
+use rustpress_core::tenant::site_filter;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::PgPool;
+use std::str::FromStr;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use uuid::Uuid;
+
+use crate::response::StreamResponse;
+
+/// Output format for an export
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Csv,
+    #[default]
+    Jsonl,
+}
+
+impl ExportFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Csv => "text/csv; charset=utf-8",
+            Self::Jsonl => "application/x-ndjson",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Jsonl => "jsonl",
+        }
+    }
+}
+
+/// Dataset that can be exported
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportDataset {
+    Posts,
+    Users,
+    Comments,
+    AnalyticsRollups,
+    AuditLogs,
+}
+
+impl ExportDataset {
+    pub const ALL: [ExportDataset; 5] = [
+        Self::Posts,
+        Self::Users,
+        Self::Comments,
+        Self::AnalyticsRollups,
+        Self::AuditLogs,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Posts => "posts",
+            Self::Users => "users",
+            Self::Comments => "comments",
+            Self::AnalyticsRollups => "analytics",
+            Self::AuditLogs => "audit-logs",
+        }
+    }
+
+    fn table(&self) -> &'static str {
+        match self {
+            Self::Posts => "posts",
+            Self::Users => "users",
+            Self::Comments => "comments",
+            Self::AnalyticsRollups => "rustanalytics_daily_data",
+            Self::AuditLogs => "audit_logs",
+        }
+    }
+
+    /// Exported columns, in output order. Secrets such as password hashes
+    /// are never included.
+    pub fn columns(&self) -> &'static [&'static str] {
+        match self {
+            Self::Posts => &[
+                "id",
+                "post_type",
+                "author_id",
+                "title",
+                "slug",
+                "status",
+                "excerpt",
+                "content",
+                "published_at",
+                "created_at",
+                "updated_at",
+            ],
+            Self::Users => &[
+                "id",
+                "email",
+                "username",
+                "display_name",
+                "role",
+                "status",
+                "locale",
+                "timezone",
+                "created_at",
+                "last_login_at",
+            ],
+            Self::Comments => &[
+                "id",
+                "post_id",
+                "parent_id",
+                "author_id",
+                "author_name",
+                "author_email",
+                "status",
+                "content",
+                "created_at",
+            ],
+            Self::AnalyticsRollups => &[
+                "id",
+                "data_date",
+                "property_id",
+                "sessions",
+                "users",
+                "new_users",
+                "pageviews",
+                "bounce_rate",
+                "goal_completions",
+                "transactions",
+                "revenue",
+            ],
+            Self::AuditLogs => &[
+                "id",
+                "user_id",
+                "action",
+                "entity_type",
+                "entity_id",
+                "old_values",
+                "new_values",
+                "ip_address",
+                "created_at",
+            ],
+        }
+    }
+
+    /// Column filtered by `since`/`until`
+    fn time_column(&self) -> &'static str {
+        match self {
+            Self::AnalyticsRollups => "data_date",
+            _ => "created_at",
+        }
+    }
+
+    /// Column filtered by `status`, if the dataset has one
+    fn status_column(&self) -> Option<&'static str> {
+        match self {
+            Self::Posts | Self::Users | Self::Comments => Some("status"),
+            Self::AuditLogs => Some("action"),
+            Self::AnalyticsRollups => None,
+        }
+    }
+
+    /// Base condition excluding rows that should never be exported, and
+    /// rows of other sites
+    fn scope(&self) -> String {
+        match self {
+            Self::Posts => format!("deleted_at IS NULL AND {}", site_filter("site_id")),
+            Self::Comments => site_filter("site_id"),
+            _ => "1=1".to_string(),
+        }
+    }
+}
+
+impl FromStr for ExportDataset {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "posts" => Ok(Self::Posts),
+            "users" => Ok(Self::Users),
+            "comments" => Ok(Self::Comments),
+            "analytics" => Ok(Self::AnalyticsRollups),
+            "audit-logs" => Ok(Self::AuditLogs),
+            other => Err(Error::invalid_input(
+                "dataset",
+                format!("Unknown export dataset '{}'", other),
+            )),
+        }
+    }
+}
+
+/// Export query parameters
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ExportParams {
+    #[serde(default)]
+    pub format: ExportFormat,
+    /// Resume after this row id
+    pub after: Option<Uuid>,
+    /// Maximum number of rows to export
+    pub limit: Option<u64>,
+    /// Only rows at or after this time
+    pub since: Option<DateTime<Utc>>,
+    /// Only rows before this time
+    pub until: Option<DateTime<Utc>>,
+    /// Only rows with this status (or action, for audit logs)
+    pub status: Option<String>,
+}
+
+/// Service producing streaming exports
+pub struct ExportService {
+    pool: PgPool,
+    chunk_size: i64,
+}
+
+impl ExportService {
+    /// Rows fetched per database round trip
+    pub const DEFAULT_CHUNK_SIZE: i64 = 1000;
+
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            chunk_size: Self::DEFAULT_CHUNK_SIZE,
+        }
+    }
+
+    pub fn with_chunk_size(mut self, chunk_size: i64) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Build the chunk query for a dataset.
+    ///
+    /// Parameters: `$1` cursor, `$2` since, `$3` until, `$4` chunk size and,
+    /// for datasets with a status column, `$5` status.
+    pub fn build_query(dataset: ExportDataset) -> String {
+        let time = dataset.time_column();
+        let status = dataset
+            .status_column()
+            .map(|column| format!(" AND ($5::TEXT IS NULL OR {}::TEXT = $5)", column))
+            .unwrap_or_default();
+
+        format!(
+            "SELECT to_jsonb(t) FROM (SELECT {} FROM {} \
+             WHERE {} AND ($1::UUID IS NULL OR id > $1) \
+             AND ($2::TIMESTAMPTZ IS NULL OR {time} >= $2) \
+             AND ($3::TIMESTAMPTZ IS NULL OR {time} < $3){} \
+             ORDER BY id LIMIT $4) t",
+            dataset.columns().join(", "),
+            dataset.table(),
+            dataset.scope(),
+            status,
+            time = time,
+        )
+    }
+
+    /// Start streaming an export.
+    ///
+    /// Rows are produced by a background task feeding a bounded channel, so
+    /// a slow client applies backpressure to the database reads. The task
+    /// stops as soon as the client disconnects.
+    pub fn stream(&self, dataset: ExportDataset, params: ExportParams) -> Result<StreamResponse> {
+        if params.status.is_some() && dataset.status_column().is_none() {
+            return Err(Error::invalid_input(
+                "status",
+                format!("The {} export has no status filter", dataset.name()),
+            ));
+        }
+
+        let (tx, rx) = mpsc::channel::<std::result::Result<Bytes, std::io::Error>>(4);
+        let pool = self.pool.clone();
+        let chunk_size = self.chunk_size;
+        let query = Self::build_query(dataset);
+
+        tokio::spawn(async move {
+            let columns = dataset.columns();
+            if params.format == ExportFormat::Csv {
+                let header = format!("{}\n", columns.join(","));
+                if tx.send(Ok(Bytes::from(header))).await.is_err() {
+                    return;
+                }
+            }
+
+            let mut cursor = params.after;
+            let mut remaining = params.limit.unwrap_or(u64::MAX);
+
+            while remaining > 0 {
+                let limit = chunk_size.min(i64::try_from(remaining).unwrap_or(i64::MAX));
+                let mut chunk = sqlx::query_as::<_, (Value,)>(&query)
+                    .bind(cursor)
+                    .bind(params.since)
+                    .bind(params.until)
+                    .bind(limit);
+                if dataset.status_column().is_some() {
+                    chunk = chunk.bind(params.status.clone());
+                }
+
+                let rows = match chunk.fetch_all(&pool).await {
+                    Ok(rows) => rows,
+                    Err(e) => {
+                        tracing::error!(dataset = dataset.name(), "Export query failed: {}", e);
+                        let _ = tx.send(Err(std::io::Error::other(e.to_string()))).await;
+                        return;
+                    }
+                };
+                if rows.is_empty() {
+                    break;
+                }
+
+                let fetched = rows.len() as i64;
+                let mut buffer = String::new();
+                for (row,) in rows {
+                    cursor = row
+                        .get("id")
+                        .and_then(Value::as_str)
+                        .and_then(|id| Uuid::parse_str(id).ok());
+                    match params.format {
+                        ExportFormat::Csv => buffer.push_str(&encode_csv_row(columns, &row)),
+                        ExportFormat::Jsonl => {
+                            buffer.push_str(&row.to_string());
+                            buffer.push('\n');
+                        }
+                    }
+                }
+
+                if tx.send(Ok(Bytes::from(buffer))).await.is_err() {
+                    tracing::debug!(dataset = dataset.name(), "Export client disconnected");
+                    return;
+                }
+
+                remaining = remaining.saturating_sub(fetched as u64);
+                if fetched < limit || cursor.is_none() {
+                    break;
+                }
+            }
+        });
+
+        let filename = format!(
+            "{}-{}.{}",
+            dataset.name(),
+            Utc::now().format("%Y%m%d%H%M%S"),
+            params.format.extension()
+        );
+
+        Ok(
+            StreamResponse::new(ReceiverStream::new(rx), params.format.content_type())
+                .with_filename(filename),
+        )
+    }
+}
+
+/// Encode one row as a CSV line using the given column order
+pub fn encode_csv_row(columns: &[&str], row: &Value) -> String {
+    let fields: Vec<String> = columns
+        .iter()
+        .map(|column| csv_field(row.get(*column).unwrap_or(&Value::Null)))
+        .collect();
+    format!("{}\n", fields.join(","))
+}
+
+fn csv_field(value: &Value) -> String {
+    let raw = match value {
+        Value::Null => return String::new(),
+        // Spreadsheets evaluate cells starting with these as formulas
+        Value::String(s) if s.starts_with(['=', '+', '-', '@', '\t', '\r']) => format!("'{}", s),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+
+    if raw.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", raw.replace('"', "\"\""))
+    } else {
+        raw
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_dataset_parsing() {
+        assert_eq!(
+            "audit-logs".parse::<ExportDataset>().unwrap(),
+            ExportDataset::AuditLogs
+        );
+        assert!("secrets".parse::<ExportDataset>().is_err());
+        assert!(!ExportDataset::Users.columns().contains(&"password_hash"));
+    }
+
+    #[test]
+    fn test_build_query() {
+        let posts = ExportService::build_query(ExportDataset::Posts);
+        assert!(posts.contains("FROM posts WHERE deleted_at IS NULL AND site_id IS NULL"));
+        assert!(posts.contains("status::TEXT = $5"));
+        assert!(posts.contains("ORDER BY id LIMIT $4"));
+
+        let analytics = ExportService::build_query(ExportDataset::AnalyticsRollups);
+        assert!(analytics.contains("data_date >= $2"));
+        assert!(!analytics.contains("$5"));
+    }
+
+    #[test]
+    fn test_csv_encoding() {
+        let row = json!({
+            "id": "1",
+            "title": "Hello, \"world\"",
+            "excerpt": null,
+            "comment_count": 3
+        });
+        assert_eq!(
+            encode_csv_row(&["id", "title", "excerpt", "comment_count"], &row),
+            "1,\"Hello, \"\"world\"\"\",,3\n"
+        );
+
+        let row = json!({"title": "=HYPERLINK(\"http://x\")", "excerpt": "@SUM(A1)", "comment_count": -2});
+        assert_eq!(
+            encode_csv_row(&["title", "excerpt", "comment_count"], &row),
+            "\"'=HYPERLINK(\"\"http://x\"\")\",'@SUM(A1),-2\n"
+        );
+    }
+}
@@ -0,0 +1,437 @@
+//! Extension Allowlists
+//!
+//! Policy controls for managed multi-tenant hosting: which themes and
+//! plugins the network, each tenant and each site may install and
+//! activate. Lists are stored in `extension_allowlists`, one row per scope.
+//! A missing row or a `NULL` list leaves that kind unrestricted, and an
+//! empty list allows nothing.
+//!
+//! The lists narrow each other: a site may only use what its tenant and
+//! the network allow. The network list is pushed into the
+//! [`ThemeManager`] and [`PluginManager`] so they refuse other extensions
+//! on their own; tenant and site lists are checked by the handlers that
+//! know which tenant and site a request is for.
+
+use chrono::{DateTime, Utc};
+use rustpress_core::error::{Error, Result};
+use rustpress_core::plugin::PluginManager;
+use rustpress_core::tenant::{ExtensionAllowlist, ExtensionKind};
+use rustpress_themes::manager::ThemeManager;
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use sqlx::{FromRow, PgPool};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+/// Most entries accepted in one bulk update
+pub const MAX_BULK_UPDATES: usize = 500;
+
+/// Longest accepted tenant ID
+const MAX_SCOPE_ID_LENGTH: usize = 100;
+
+/// Level an allowlist applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AllowlistScope {
+    Network,
+    Tenant,
+    Site,
+}
+
+impl AllowlistScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Network => "network",
+            Self::Tenant => "tenant",
+            Self::Site => "site",
+        }
+    }
+}
+
+impl fmt::Display for AllowlistScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for AllowlistScope {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "network" => Ok(Self::Network),
+            "tenant" => Ok(Self::Tenant),
+            "site" => Ok(Self::Site),
+            _ => Err(Error::invalid_input(
+                "scope",
+                format!(
+                    "Unknown scope '{}'; expected one of: network, tenant, site",
+                    s
+                ),
+            )),
+        }
+    }
+}
+
+/// A stored allowlist
+#[derive(Debug, Clone, Serialize)]
+pub struct AllowlistEntry {
+    pub scope: AllowlistScope,
+    /// Tenant ID or site UUID; empty for the network
+    pub scope_id: String,
+    #[serde(flatten)]
+    pub allowlist: ExtensionAllowlist,
+    pub updated_by: Option<Uuid>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// One change in a bulk update
+#[derive(Debug, Clone, Deserialize)]
+pub struct AllowlistUpdate {
+    pub scope: AllowlistScope,
+    #[serde(default)]
+    pub scope_id: String,
+    /// Replaces both lists; omitted lists become unrestricted
+    #[serde(flatten)]
+    pub allowlist: ExtensionAllowlist,
+    /// Remove the scope's allowlist instead
+    #[serde(default)]
+    pub delete: bool,
+}
+
+impl AllowlistUpdate {
+    /// Validate the scope ID and trim, de-duplicate and check extension IDs
+    fn normalize(mut self) -> Result<Self> {
+        self.scope_id = self.scope_id.trim().to_string();
+        match self.scope {
+            AllowlistScope::Network if !self.scope_id.is_empty() => {
+                return Err(Error::invalid_input(
+                    "scope_id",
+                    "The network allowlist does not take a scope ID",
+                ));
+            }
+            AllowlistScope::Tenant
+                if self.scope_id.is_empty() || self.scope_id.len() > MAX_SCOPE_ID_LENGTH =>
+            {
+                return Err(Error::invalid_input(
+                    "scope_id",
+                    format!(
+                        "Tenant ID is required and must be at most {} characters",
+                        MAX_SCOPE_ID_LENGTH
+                    ),
+                ));
+            }
+            AllowlistScope::Site => {
+                let site_id = Uuid::parse_str(&self.scope_id).map_err(|_| {
+                    Error::invalid_input("scope_id", "Site allowlists need a site UUID")
+                })?;
+                self.scope_id = site_id.to_string();
+            }
+            _ => {}
+        }
+
+        for (field, ids) in [
+            ("themes", &mut self.allowlist.themes),
+            ("plugins", &mut self.allowlist.plugins),
+        ] {
+            if let Some(list) = ids.take() {
+                let mut normalized = BTreeSet::new();
+                for id in list {
+                    let id = id.trim().to_string();
+                    if !is_extension_id(&id) {
+                        return Err(Error::invalid_input(
+                            field,
+                            format!("Invalid extension ID '{}'", id),
+                        ));
+                    }
+                    normalized.insert(id);
+                }
+                *ids = Some(normalized);
+            }
+        }
+
+        Ok(self)
+    }
+}
+
+fn is_extension_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= 100
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+#[derive(Debug, FromRow)]
+struct AllowlistRow {
+    scope: String,
+    scope_id: String,
+    themes: Option<Json<BTreeSet<String>>>,
+    plugins: Option<Json<BTreeSet<String>>>,
+    updated_by: Option<Uuid>,
+    updated_at: DateTime<Utc>,
+}
+
+impl AllowlistRow {
+    fn into_entry(self) -> Result<AllowlistEntry> {
+        Ok(AllowlistEntry {
+            scope: self.scope.parse()?,
+            scope_id: self.scope_id,
+            allowlist: ExtensionAllowlist {
+                themes: self.themes.map(|ids| ids.0),
+                plugins: self.plugins.map(|ids| ids.0),
+            },
+            updated_by: self.updated_by,
+            updated_at: self.updated_at,
+        })
+    }
+}
+
+type AllowlistMap = HashMap<(AllowlistScope, String), ExtensionAllowlist>;
+
+/// Stores allowlists and enforces them across scopes
+pub struct ExtensionAllowlistService {
+    pool: PgPool,
+    plugins: Arc<RwLock<PluginManager>>,
+    themes: Arc<ThemeManager>,
+    entries: RwLock<Option<Arc<AllowlistMap>>>,
+}
+
+impl ExtensionAllowlistService {
+    pub fn new(
+        pool: PgPool,
+        plugins: Arc<RwLock<PluginManager>>,
+        themes: Arc<ThemeManager>,
+    ) -> Self {
+        Self {
+            pool,
+            plugins,
+            themes,
+            entries: RwLock::new(None),
+        }
+    }
+
+    /// Load every allowlist and apply the network list to the theme and
+    /// plugin managers
+    pub async fn load(&self) -> Result<()> {
+        let entries = self.list().await?;
+        let map: AllowlistMap = entries
+            .into_iter()
+            .map(|entry| ((entry.scope, entry.scope_id), entry.allowlist))
+            .collect();
+
+        let network = map
+            .get(&(AllowlistScope::Network, String::new()))
+            .cloned()
+            .unwrap_or_default();
+        self.themes.set_allowlist(network.themes);
+        self.plugins.read().await.set_allowlist(network.plugins);
+
+        *self.entries.write().await = Some(Arc::new(map));
+        Ok(())
+    }
+
+    async fn entries(&self) -> Arc<AllowlistMap> {
+        if let Some(entries) = self.entries.read().await.as_ref() {
+            return entries.clone();
+        }
+        if let Err(e) = self.load().await {
+            tracing::warn!("Failed to load extension allowlists, allowing all: {}", e);
+            return Arc::new(AllowlistMap::new());
+        }
+        self.entries.read().await.clone().unwrap_or_default()
+    }
+
+    /// Every stored allowlist
+    pub async fn list(&self) -> Result<Vec<AllowlistEntry>> {
+        let rows: Vec<AllowlistRow> = sqlx::query_as(
+            "SELECT scope, scope_id, themes, plugins, updated_by, updated_at \
+             FROM extension_allowlists ORDER BY scope, scope_id",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to list extension allowlists", e))?;
+
+        rows.into_iter().map(AllowlistRow::into_entry).collect()
+    }
+
+    /// Apply a batch of changes in one transaction, then reload
+    pub async fn bulk_update(
+        &self,
+        updates: Vec<AllowlistUpdate>,
+        user_id: Uuid,
+    ) -> Result<Vec<AllowlistEntry>> {
+        if updates.is_empty() {
+            return Err(Error::invalid_input("updates", "No changes given"));
+        }
+        if updates.len() > MAX_BULK_UPDATES {
+            return Err(Error::invalid_input(
+                "updates",
+                format!(
+                    "At most {} changes can be applied at once",
+                    MAX_BULK_UPDATES
+                ),
+            ));
+        }
+        let updates = updates
+            .into_iter()
+            .map(AllowlistUpdate::normalize)
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| Error::database_with_source("Failed to start transaction", e))?;
+
+        for update in &updates {
+            if update.delete {
+                sqlx::query("DELETE FROM extension_allowlists WHERE scope = $1 AND scope_id = $2")
+                    .bind(update.scope.as_str())
+                    .bind(&update.scope_id)
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(|e| {
+                        Error::database_with_source("Failed to delete extension allowlist", e)
+                    })?;
+                continue;
+            }
+
+            sqlx::query(
+                "INSERT INTO extension_allowlists \
+                     (scope, scope_id, themes, plugins, updated_by, updated_at) \
+                 VALUES ($1, $2, $3, $4, $5, NOW()) \
+                 ON CONFLICT (scope, scope_id) DO UPDATE SET \
+                     themes = EXCLUDED.themes, plugins = EXCLUDED.plugins, \
+                     updated_by = EXCLUDED.updated_by, updated_at = NOW()",
+            )
+            .bind(update.scope.as_str())
+            .bind(&update.scope_id)
+            .bind(update.allowlist.themes.clone().map(Json))
+            .bind(update.allowlist.plugins.clone().map(Json))
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to save extension allowlist", e))?;
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| Error::database_with_source("Failed to commit allowlists", e))?;
+
+        self.load().await?;
+        self.list().await
+    }
+
+    /// What a tenant and site may use once every applicable list is applied
+    pub async fn effective(&self, tenant: Option<&str>, site: Option<Uuid>) -> ExtensionAllowlist {
+        self.applicable(tenant, site)
+            .await
+            .iter()
+            .fold(ExtensionAllowlist::default(), |acc, (_, list)| {
+                acc.intersect(list)
+            })
+    }
+
+    /// Fail unless the network, the tenant and the site all allow the
+    /// extension, naming the scope that refused it
+    pub async fn check(
+        &self,
+        kind: ExtensionKind,
+        id: &str,
+        tenant: Option<&str>,
+        site: Option<Uuid>,
+    ) -> Result<()> {
+        for (scope, list) in self.applicable(tenant, site).await {
+            list.check(kind, id, &scope)?;
+        }
+        Ok(())
+    }
+
+    /// Lists that apply to a request, broadest first, with the scope
+    /// description used in errors
+    async fn applicable(
+        &self,
+        tenant: Option<&str>,
+        site: Option<Uuid>,
+    ) -> Vec<(String, ExtensionAllowlist)> {
+        let entries = self.entries().await;
+        let mut keys = vec![(
+            "this network".to_string(),
+            (AllowlistScope::Network, String::new()),
+        )];
+        if let Some(tenant) = tenant {
+            keys.push((
+                format!("tenant '{}'", tenant),
+                (AllowlistScope::Tenant, tenant.to_string()),
+            ));
+        }
+        if let Some(site) = site {
+            keys.push((
+                format!("site {}", site),
+                (AllowlistScope::Site, site.to_string()),
+            ));
+        }
+
+        keys.into_iter()
+            .filter_map(|(scope, key)| entries.get(&key).map(|list| (scope, list.clone())))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_update() {
+        let update = AllowlistUpdate {
+            scope: AllowlistScope::Tenant,
+            scope_id: " acme ".to_string(),
+            allowlist: ExtensionAllowlist {
+                themes: Some(BTreeSet::from([
+                    " starter".to_string(),
+                    "starter".to_string(),
+                ])),
+                plugins: None,
+            },
+            delete: false,
+        }
+        .normalize()
+        .unwrap();
+        assert_eq!(update.scope_id, "acme");
+        assert_eq!(update.allowlist.themes.unwrap().len(), 1);
+        assert!(update.allowlist.plugins.is_none());
+
+        let network_with_id = AllowlistUpdate {
+            scope: AllowlistScope::Network,
+            scope_id: "acme".to_string(),
+            allowlist: ExtensionAllowlist::default(),
+            delete: false,
+        };
+        assert!(network_with_id.normalize().is_err());
+
+        let bad_site = AllowlistUpdate {
+            scope: AllowlistScope::Site,
+            scope_id: "not-a-uuid".to_string(),
+            allowlist: ExtensionAllowlist::default(),
+            delete: true,
+        };
+        assert!(bad_site.normalize().is_err());
+
+        let bad_id = AllowlistUpdate {
+            scope: AllowlistScope::Network,
+            scope_id: String::new(),
+            allowlist: ExtensionAllowlist {
+                themes: None,
+                plugins: Some(BTreeSet::from(["../evil".to_string()])),
+            },
+            delete: false,
+        };
+        assert!(bad_id.normalize().is_err());
+    }
+}
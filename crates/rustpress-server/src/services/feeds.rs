@@ -0,0 +1,844 @@
+//! Syndication Feeds
+//!
+//! Builds the site feed (`/feed`) and the archive feeds (category, tag,
+//! author and date archives) in RSS 2.0, Atom 1.0 and JSON Feed 1.1.
+//! The format is chosen by a path suffix: `{feed}` and `{feed}/rss` serve
+//! RSS, `{feed}/atom` serves Atom and `{feed}/json` serves JSON Feed.
+//!
+//! Feed responses carry `ETag` and `Last-Modified` validators, so readers
+//! polling with `If-None-Match` or `If-Modified-Since` get a `304` when
+//! nothing changed.
+//!
+//! Podcast episodes carry their audio as an enclosure in every format, and
+//! the podcast feed (`/podcast/feed`) adds the iTunes and Podcast Index
+//! namespaces podcast directories read.
+
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+
+use super::archives::ArchiveQuery;
+use super::podcast::{EpisodeType, PodcastConfig};
+use super::render_service::{PostData, RenderedPage, SiteInfo};
+
+/// Feed document format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeedFormat {
+    Rss,
+    Atom,
+    Json,
+}
+
+impl FeedFormat {
+    /// Format for a feed path suffix (`rss`, `atom`, `json`)
+    pub fn from_segment(segment: &str) -> Option<Self> {
+        match segment {
+            "rss" => Some(Self::Rss),
+            "atom" => Some(Self::Atom),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+
+    /// Path suffix of the format; RSS is served from the bare feed URL
+    pub fn segment(&self) -> Option<&'static str> {
+        match self {
+            Self::Rss => None,
+            Self::Atom => Some("atom"),
+            Self::Json => Some("json"),
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Rss => "application/rss+xml; charset=utf-8",
+            Self::Atom => "application/atom+xml; charset=utf-8",
+            Self::Json => "application/feed+json; charset=utf-8",
+        }
+    }
+}
+
+/// The posts a feed covers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FeedScope {
+    /// Latest posts of the whole site
+    Site,
+    /// Posts of a category, tag, author or date archive
+    Archive(ArchiveQuery),
+}
+
+impl FeedScope {
+    /// Path of the HTML page the feed mirrors
+    pub fn page_path(&self) -> String {
+        match self {
+            Self::Site => "/".to_string(),
+            Self::Archive(query) => query.page_path(1),
+        }
+    }
+
+    /// Path of the feed in a format, keeping archive filters in the query
+    /// string (`/category/news/feed/atom?tag=rust`)
+    pub fn feed_path(&self, format: FeedFormat) -> String {
+        let rss = match self {
+            Self::Site => "/feed".to_string(),
+            Self::Archive(query) => query.feed_path(),
+        };
+        let Some(segment) = format.segment() else {
+            return rss;
+        };
+        match rss.split_once('?') {
+            Some((path, query)) => format!("{}/{}?{}", path, segment, query),
+            None => format!("{}/{}", rss, segment),
+        }
+    }
+}
+
+/// Channel-level metadata of a feed
+#[derive(Debug, Clone)]
+pub struct FeedChannel<'a> {
+    pub site: &'a SiteInfo,
+    pub title: String,
+    pub description: String,
+    /// Path of the HTML page the feed mirrors
+    pub link: String,
+    /// Path of the feed itself
+    pub self_link: String,
+}
+
+impl FeedChannel<'_> {
+    fn site_url(&self) -> &str {
+        self.site.url.trim_end_matches('/')
+    }
+
+    fn post_url(&self, post: &PostData) -> String {
+        format!("{}/post/{}", self.site_url(), post.slug)
+    }
+
+    /// Absolute form of a site-relative URL
+    fn absolute_url(&self, url: &str) -> String {
+        if url.starts_with('/') {
+            format!("{}{}", self.site_url(), url)
+        } else {
+            url.to_string()
+        }
+    }
+}
+
+/// A rendered feed with the modification time of its newest post
+#[derive(Debug)]
+pub struct RenderedFeed {
+    pub page: RenderedPage,
+    pub last_modified: Option<DateTime<Utc>>,
+}
+
+/// Render a built-in feed document, used when the theme has no feed template
+pub fn render_feed(format: FeedFormat, channel: &FeedChannel<'_>, posts: &[PostData]) -> String {
+    match format {
+        FeedFormat::Rss => render_rss(channel, posts),
+        FeedFormat::Atom => render_atom(channel, posts),
+        FeedFormat::Json => render_json_feed(channel, posts),
+    }
+}
+
+/// Most recent modification among the posts of a feed
+pub fn last_modified(posts: &[PostData]) -> Option<DateTime<Utc>> {
+    posts.iter().map(|post| post.updated_at).max()
+}
+
+/// Render an RSS 2.0 feed
+pub fn render_rss(channel: &FeedChannel<'_>, posts: &[PostData]) -> String {
+    let site_url = channel.site_url();
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str("<rss version=\"2.0\" xmlns:atom=\"http://www.w3.org/2005/Atom\">\n<channel>\n");
+    xml.push_str(&format!(
+        "  <title>{}</title>\n",
+        xml_escape(&channel.title)
+    ));
+    xml.push_str(&format!(
+        "  <link>{}{}</link>\n",
+        site_url,
+        xml_escape(&channel.link)
+    ));
+    xml.push_str(&format!(
+        "  <description>{}</description>\n",
+        xml_escape(&channel.description)
+    ));
+    xml.push_str(&format!(
+        "  <language>{}</language>\n",
+        xml_escape(&channel.site.language)
+    ));
+    if let Some(updated) = last_modified(posts) {
+        xml.push_str(&format!(
+            "  <lastBuildDate>{}</lastBuildDate>\n",
+            updated.to_rfc2822()
+        ));
+    }
+    xml.push_str(&format!(
+        "  <atom:link href=\"{}{}\" rel=\"self\" type=\"application/rss+xml\"/>\n",
+        site_url,
+        xml_escape(&channel.self_link)
+    ));
+
+    for post in posts {
+        let url = channel.post_url(post);
+        xml.push_str("  <item>\n");
+        xml.push_str(&format!("    <title>{}</title>\n", xml_escape(&post.title)));
+        xml.push_str(&format!("    <link>{}</link>\n", xml_escape(&url)));
+        xml.push_str(&format!(
+            "    <guid isPermaLink=\"true\">{}</guid>\n",
+            xml_escape(&url)
+        ));
+        if let Some(published) = post.published_at {
+            xml.push_str(&format!(
+                "    <pubDate>{}</pubDate>\n",
+                published.to_rfc2822()
+            ));
+        }
+        xml.push_str(&format!(
+            "    <dc:creator xmlns:dc=\"http://purl.org/dc/elements/1.1/\">{}</dc:creator>\n",
+            xml_escape(&post.author.name)
+        ));
+        for term in post.categories.iter().chain(&post.tags) {
+            xml.push_str(&format!(
+                "    <category>{}</category>\n",
+                xml_escape(&term.name)
+            ));
+        }
+        if let Some(ref excerpt) = post.excerpt {
+            xml.push_str(&format!(
+                "    <description>{}</description>\n",
+                xml_escape(excerpt)
+            ));
+        }
+        push_enclosure(&mut xml, channel, post);
+        xml.push_str("  </item>\n");
+    }
+
+    xml.push_str("</channel>\n</rss>\n");
+    xml
+}
+
+fn push_enclosure(xml: &mut String, channel: &FeedChannel<'_>, post: &PostData) {
+    if let Some(ref episode) = post.episode {
+        xml.push_str(&format!(
+            "    <enclosure url=\"{}\" length=\"{}\" type=\"{}\"/>\n",
+            xml_escape(&channel.absolute_url(&episode.url)),
+            episode.file_size,
+            xml_escape(&episode.mime_type)
+        ));
+    }
+}
+
+/// Render the podcast feed: RSS 2.0 with the iTunes and Podcast Index
+/// namespaces, listing episodes only
+pub fn render_podcast_rss(
+    channel: &FeedChannel<'_>,
+    show: &PodcastConfig,
+    posts: &[PostData],
+) -> String {
+    let site_url = channel.site_url();
+    let language = show.language.as_deref().unwrap_or(&channel.site.language);
+    let author = if show.author.is_empty() {
+        &channel.site.author
+    } else {
+        &show.author
+    };
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(
+        "<rss version=\"2.0\" xmlns:atom=\"http://www.w3.org/2005/Atom\" \
+         xmlns:itunes=\"http://www.itunes.com/dtds/podcast-1.0.dtd\" \
+         xmlns:podcast=\"https://podcastindex.org/namespace/1.0\" \
+         xmlns:content=\"http://purl.org/rss/1.0/modules/content/\">\n<channel>\n",
+    );
+    xml.push_str(&format!(
+        "  <title>{}</title>\n",
+        xml_escape(&channel.title)
+    ));
+    xml.push_str(&format!(
+        "  <link>{}{}</link>\n",
+        site_url,
+        xml_escape(&channel.link)
+    ));
+    xml.push_str(&format!(
+        "  <description>{}</description>\n",
+        xml_escape(&channel.description)
+    ));
+    xml.push_str(&format!(
+        "  <language>{}</language>\n",
+        xml_escape(language)
+    ));
+    if let Some(ref copyright) = show.copyright {
+        xml.push_str(&format!(
+            "  <copyright>{}</copyright>\n",
+            xml_escape(copyright)
+        ));
+    }
+    if let Some(updated) = last_modified(posts) {
+        xml.push_str(&format!(
+            "  <lastBuildDate>{}</lastBuildDate>\n",
+            updated.to_rfc2822()
+        ));
+    }
+    xml.push_str(&format!(
+        "  <atom:link href=\"{}{}\" rel=\"self\" type=\"application/rss+xml\"/>\n",
+        site_url,
+        xml_escape(&channel.self_link)
+    ));
+
+    xml.push_str(&format!(
+        "  <itunes:author>{}</itunes:author>\n",
+        xml_escape(author)
+    ));
+    if !show.owner_name.is_empty() || !show.owner_email.is_empty() {
+        xml.push_str(&format!(
+            "  <itunes:owner><itunes:name>{}</itunes:name><itunes:email>{}</itunes:email></itunes:owner>\n",
+            xml_escape(&show.owner_name),
+            xml_escape(&show.owner_email)
+        ));
+    }
+    if let Some(image) = show.image_url.as_deref().filter(|u| !u.is_empty()) {
+        let image = xml_escape(&channel.absolute_url(image));
+        xml.push_str(&format!("  <itunes:image href=\"{}\"/>\n", image));
+        xml.push_str(&format!(
+            "  <image><url>{}</url><title>{}</title><link>{}{}</link></image>\n",
+            image,
+            xml_escape(&channel.title),
+            site_url,
+            xml_escape(&channel.link)
+        ));
+    }
+    for (category, subcategory) in show.itunes_categories() {
+        match subcategory {
+            Some(subcategory) => xml.push_str(&format!(
+                "  <itunes:category text=\"{}\"><itunes:category text=\"{}\"/></itunes:category>\n",
+                xml_escape(category),
+                xml_escape(subcategory)
+            )),
+            None => xml.push_str(&format!(
+                "  <itunes:category text=\"{}\"/>\n",
+                xml_escape(category)
+            )),
+        }
+    }
+    xml.push_str(&format!(
+        "  <itunes:explicit>{}</itunes:explicit>\n",
+        show.explicit
+    ));
+    xml.push_str(&format!(
+        "  <itunes:type>{}</itunes:type>\n",
+        show.show_type.as_str()
+    ));
+    if let Some(guid) = show.guid {
+        xml.push_str(&format!("  <podcast:guid>{}</podcast:guid>\n", guid));
+    }
+    xml.push_str(&format!(
+        "  <podcast:locked>{}</podcast:locked>\n",
+        if show.locked { "yes" } else { "no" }
+    ));
+    if let Some(url) = show.funding_url.as_deref().filter(|u| !u.is_empty()) {
+        xml.push_str(&format!(
+            "  <podcast:funding url=\"{}\">{}</podcast:funding>\n",
+            xml_escape(url),
+            xml_escape(show.funding_text.as_deref().unwrap_or("Support the show"))
+        ));
+    }
+
+    for post in posts {
+        let Some(ref episode) = post.episode else {
+            continue;
+        };
+        let url = channel.post_url(post);
+        xml.push_str("  <item>\n");
+        xml.push_str(&format!("    <title>{}</title>\n", xml_escape(&post.title)));
+        xml.push_str(&format!("    <link>{}</link>\n", xml_escape(&url)));
+        // Directories key episodes on the GUID, which must survive slug changes
+        xml.push_str(&format!(
+            "    <guid isPermaLink=\"false\">{}</guid>\n",
+            xml_escape(&post.id)
+        ));
+        if let Some(published) = post.published_at {
+            xml.push_str(&format!(
+                "    <pubDate>{}</pubDate>\n",
+                published.to_rfc2822()
+            ));
+        }
+        if let Some(ref excerpt) = post.excerpt {
+            xml.push_str(&format!(
+                "    <description>{}</description>\n",
+                xml_escape(excerpt)
+            ));
+        }
+        xml.push_str(&format!(
+            "    <content:encoded>{}</content:encoded>\n",
+            xml_escape(&post.content)
+        ));
+        push_enclosure(&mut xml, channel, post);
+        xml.push_str(&format!(
+            "    <itunes:duration>{}</itunes:duration>\n",
+            episode.duration_seconds
+        ));
+        if let Some(season) = episode.season {
+            xml.push_str(&format!("    <itunes:season>{}</itunes:season>\n", season));
+        }
+        if let Some(number) = episode.episode_number {
+            xml.push_str(&format!(
+                "    <itunes:episode>{}</itunes:episode>\n",
+                number
+            ));
+        }
+        if episode.episode_type != EpisodeType::Full.as_str() {
+            xml.push_str(&format!(
+                "    <itunes:episodeType>{}</itunes:episodeType>\n",
+                xml_escape(&episode.episode_type)
+            ));
+        }
+        if let Some(explicit) = episode.explicit {
+            xml.push_str(&format!(
+                "    <itunes:explicit>{}</itunes:explicit>\n",
+                explicit
+            ));
+        }
+        if let Some(ref image) = post.featured_image {
+            xml.push_str(&format!(
+                "    <itunes:image href=\"{}\"/>\n",
+                xml_escape(&channel.absolute_url(&image.url))
+            ));
+        }
+        if let Some(ref transcript) = episode.transcript_url {
+            xml.push_str(&format!(
+                "    <podcast:transcript url=\"{}\" type=\"{}\"/>\n",
+                xml_escape(&channel.absolute_url(transcript)),
+                transcript_type(transcript)
+            ));
+        }
+        xml.push_str("  </item>\n");
+    }
+
+    xml.push_str("</channel>\n</rss>\n");
+    xml
+}
+
+/// MIME type of a transcript, by extension
+fn transcript_type(url: &str) -> &'static str {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    match path.rsplit('.').next() {
+        Some("vtt") => "text/vtt",
+        Some("srt") => "application/x-subrip",
+        Some("json") => "application/json",
+        Some("html") | Some("htm") => "text/html",
+        _ => "text/plain",
+    }
+}
+
+/// Render an Atom 1.0 feed
+pub fn render_atom(channel: &FeedChannel<'_>, posts: &[PostData]) -> String {
+    let site_url = channel.site_url();
+    let self_url = format!("{}{}", site_url, channel.self_link);
+    // Atom requires `updated`; an empty feed reports the epoch so the
+    // document (and its ETag) stays stable
+    let updated = last_modified(posts).unwrap_or_default();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<feed xmlns=\"http://www.w3.org/2005/Atom\" xml:lang=\"{}\">\n",
+        xml_escape(&channel.site.language)
+    ));
+    xml.push_str(&format!(
+        "  <title>{}</title>\n",
+        xml_escape(&channel.title)
+    ));
+    if !channel.description.is_empty() {
+        xml.push_str(&format!(
+            "  <subtitle>{}</subtitle>\n",
+            xml_escape(&channel.description)
+        ));
+    }
+    xml.push_str(&format!("  <id>{}</id>\n", xml_escape(&self_url)));
+    xml.push_str(&format!(
+        "  <link href=\"{}{}\"/>\n",
+        site_url,
+        xml_escape(&channel.link)
+    ));
+    xml.push_str(&format!(
+        "  <link href=\"{}\" rel=\"self\" type=\"application/atom+xml\"/>\n",
+        xml_escape(&self_url)
+    ));
+    xml.push_str(&format!("  <updated>{}</updated>\n", updated.to_rfc3339()));
+    xml.push_str("  <generator>RustPress</generator>\n");
+
+    for post in posts {
+        let url = channel.post_url(post);
+        xml.push_str("  <entry>\n");
+        xml.push_str(&format!("    <title>{}</title>\n", xml_escape(&post.title)));
+        xml.push_str(&format!("    <id>{}</id>\n", xml_escape(&url)));
+        xml.push_str(&format!(
+            "    <link href=\"{}\" rel=\"alternate\" type=\"text/html\"/>\n",
+            xml_escape(&url)
+        ));
+        if let Some(ref episode) = post.episode {
+            xml.push_str(&format!(
+                "    <link href=\"{}\" rel=\"enclosure\" type=\"{}\" length=\"{}\"/>\n",
+                xml_escape(&channel.absolute_url(&episode.url)),
+                xml_escape(&episode.mime_type),
+                episode.file_size
+            ));
+        }
+        if let Some(published) = post.published_at {
+            xml.push_str(&format!(
+                "    <published>{}</published>\n",
+                published.to_rfc3339()
+            ));
+        }
+        xml.push_str(&format!(
+            "    <updated>{}</updated>\n",
+            post.updated_at.to_rfc3339()
+        ));
+        xml.push_str(&format!(
+            "    <author><name>{}</name></author>\n",
+            xml_escape(&post.author.name)
+        ));
+        for term in post.categories.iter().chain(&post.tags) {
+            xml.push_str(&format!(
+                "    <category term=\"{}\" label=\"{}\"/>\n",
+                xml_escape(&term.slug),
+                xml_escape(&term.name)
+            ));
+        }
+        if let Some(ref excerpt) = post.excerpt {
+            xml.push_str(&format!("    <summary>{}</summary>\n", xml_escape(excerpt)));
+        }
+        xml.push_str(&format!(
+            "    <content type=\"html\">{}</content>\n",
+            xml_escape(&post.content)
+        ));
+        xml.push_str("  </entry>\n");
+    }
+
+    xml.push_str("</feed>\n");
+    xml
+}
+
+/// Render a JSON Feed 1.1 document
+pub fn render_json_feed(channel: &FeedChannel<'_>, posts: &[PostData]) -> String {
+    let site_url = channel.site_url();
+    let items: Vec<serde_json::Value> = posts
+        .iter()
+        .map(|post| {
+            let url = channel.post_url(post);
+            let mut item = serde_json::json!({
+                "id": &url,
+                "url": &url,
+                "title": &post.title,
+                "content_html": &post.content,
+                "date_modified": post.updated_at.to_rfc3339(),
+                "authors": [{
+                    "name": &post.author.name,
+                    "url": format!("{}/author/{}", site_url, post.author.slug),
+                }],
+            });
+            if let Some(ref excerpt) = post.excerpt {
+                item["summary"] = excerpt.as_str().into();
+            }
+            if let Some(published) = post.published_at {
+                item["date_published"] = published.to_rfc3339().into();
+            }
+            if let Some(ref image) = post.featured_image {
+                item["image"] = image.url.as_str().into();
+            }
+            let tags: Vec<&str> = post
+                .categories
+                .iter()
+                .chain(&post.tags)
+                .map(|term| term.name.as_str())
+                .collect();
+            if !tags.is_empty() {
+                item["tags"] = tags.into();
+            }
+            if let Some(ref episode) = post.episode {
+                item["attachments"] = serde_json::json!([{
+                    "url": channel.absolute_url(&episode.url),
+                    "mime_type": &episode.mime_type,
+                    "size_in_bytes": episode.file_size,
+                    "duration_in_seconds": episode.duration_seconds,
+                }]);
+            }
+            item
+        })
+        .collect();
+
+    let feed = serde_json::json!({
+        "version": "https://jsonfeed.org/version/1.1",
+        "title": &channel.title,
+        "description": &channel.description,
+        "home_page_url": format!("{}{}", site_url, channel.link),
+        "feed_url": format!("{}{}", site_url, channel.self_link),
+        "language": &channel.site.language,
+        "items": items,
+    });
+    serde_json::to_string_pretty(&feed).unwrap_or_default()
+}
+
+/// Cache validators of a rendered feed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeedValidators {
+    pub etag: String,
+    pub last_modified: Option<DateTime<Utc>>,
+}
+
+impl FeedValidators {
+    /// Strong ETag over the feed body
+    pub fn new(body: &str, last_modified: Option<DateTime<Utc>>) -> Self {
+        let digest = format!("{:x}", Sha256::digest(body.as_bytes()));
+        Self {
+            etag: format!("\"{}\"", &digest[..32]),
+            last_modified,
+        }
+    }
+
+    /// `Last-Modified` header value (IMF-fixdate)
+    pub fn last_modified_header(&self) -> Option<String> {
+        self.last_modified
+            .map(|time| time.format("%a, %d %b %Y %H:%M:%S GMT").to_string())
+    }
+
+    /// Whether a conditional GET can be answered with `304 Not Modified`.
+    ///
+    /// `If-None-Match` takes precedence; `If-Modified-Since` is only
+    /// consulted when the client sent no entity tags.
+    pub fn not_modified(
+        &self,
+        if_none_match: Option<&str>,
+        if_modified_since: Option<&str>,
+    ) -> bool {
+        if let Some(tags) = if_none_match {
+            return etag_matches(tags, &self.etag);
+        }
+
+        let (Some(since), Some(modified)) = (if_modified_since, self.last_modified) else {
+            return false;
+        };
+        match DateTime::parse_from_rfc2822(since.trim()) {
+            // HTTP dates have second precision
+            Ok(since) => modified.timestamp() <= since.timestamp(),
+            Err(_) => false,
+        }
+    }
+}
+
+/// Whether an `If-None-Match` header matches an entity tag (weak comparison)
+pub fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    let etag = etag.strip_prefix("W/").unwrap_or(etag);
+    if_none_match
+        .split(',')
+        .map(str::trim)
+        .any(|tag| tag == "*" || tag.strip_prefix("W/").unwrap_or(tag) == etag)
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::podcast::EpisodeData;
+    use super::super::render_service::{AuthorData, TermData};
+    use super::*;
+    use chrono::TimeZone;
+    use std::collections::HashMap;
+
+    fn site() -> SiteInfo {
+        SiteInfo {
+            name: "Example".to_string(),
+            description: "News & notes".to_string(),
+            url: "https://example.com/".to_string(),
+            language: "en-US".to_string(),
+            charset: "UTF-8".to_string(),
+            default_image: String::new(),
+            author: "Admin".to_string(),
+        }
+    }
+
+    fn post(slug: &str, updated: DateTime<Utc>) -> PostData {
+        PostData {
+            id: slug.to_string(),
+            short_id: None,
+            shortlink: None,
+            title: format!("Post <{}>", slug),
+            slug: slug.to_string(),
+            content: "<p>Hello</p>".to_string(),
+            excerpt: Some("Hello".to_string()),
+            post_type: "post".to_string(),
+            status: "published".to_string(),
+            author: AuthorData {
+                id: "a1".to_string(),
+                name: "Ada".to_string(),
+                slug: "ada".to_string(),
+                bio: None,
+                avatar_url: None,
+                url: None,
+                profile: None,
+            },
+            featured_image: None,
+            categories: vec![TermData {
+                id: "t1".to_string(),
+                name: "News".to_string(),
+                slug: "news".to_string(),
+                description: None,
+                count: 1,
+                taxonomy: "category".to_string(),
+            }],
+            tags: Vec::new(),
+            created_at: updated,
+            updated_at: updated,
+            published_at: Some(updated),
+            comment_count: 0,
+            meta: HashMap::new(),
+            episode: None,
+            event: None,
+        }
+    }
+
+    #[test]
+    fn test_feed_paths() {
+        assert_eq!(FeedScope::Site.feed_path(FeedFormat::Rss), "/feed");
+        assert_eq!(FeedScope::Site.feed_path(FeedFormat::Json), "/feed/json");
+
+        let archive = ArchiveQuery::terms("category", "news", &[("tag", "rust")]).unwrap();
+        let scope = FeedScope::Archive(archive);
+        assert_eq!(scope.page_path(), "/category/news?tag=rust");
+        assert_eq!(
+            scope.feed_path(FeedFormat::Atom),
+            "/category/news/feed/atom?tag=rust"
+        );
+
+        assert_eq!(FeedFormat::from_segment("json"), Some(FeedFormat::Json));
+        assert_eq!(FeedFormat::from_segment("xml"), None);
+    }
+
+    #[test]
+    fn test_render_formats() {
+        let site = site();
+        let channel = FeedChannel {
+            site: &site,
+            title: "News - Example".to_string(),
+            description: site.description.clone(),
+            link: "/category/news".to_string(),
+            self_link: "/category/news/feed/atom".to_string(),
+        };
+        let older = Utc.with_ymd_and_hms(2024, 5, 1, 8, 0, 0).unwrap();
+        let newer = Utc.with_ymd_and_hms(2024, 5, 2, 8, 0, 0).unwrap();
+        let posts = vec![post("b", newer), post("a", older)];
+
+        let rss = render_feed(FeedFormat::Rss, &channel, &posts);
+        assert!(rss.contains("<title>Post &lt;b&gt;</title>"));
+        assert!(rss.contains("<link>https://example.com/category/news</link>"));
+
+        let atom = render_feed(FeedFormat::Atom, &channel, &posts);
+        assert!(atom.contains("<updated>2024-05-02T08:00:00+00:00</updated>"));
+        assert!(atom.contains("<id>https://example.com/post/a</id>"));
+        assert!(atom.contains("<content type=\"html\">&lt;p&gt;Hello&lt;/p&gt;</content>"));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&render_feed(FeedFormat::Json, &channel, &posts)).unwrap();
+        assert_eq!(json["version"], "https://jsonfeed.org/version/1.1");
+        assert_eq!(json["items"][0]["url"], "https://example.com/post/b");
+        assert_eq!(json["items"][0]["tags"][0], "News");
+        assert_eq!(json["items"][1]["authors"][0]["name"], "Ada");
+    }
+
+    #[test]
+    fn test_podcast_feed() {
+        let site = site();
+        let channel = FeedChannel {
+            site: &site,
+            title: "Example Podcast".to_string(),
+            description: "Talk & music".to_string(),
+            link: "/".to_string(),
+            self_link: "/podcast/feed".to_string(),
+        };
+        let show = PodcastConfig {
+            author: "Ada".to_string(),
+            owner_email: "ada@example.com".to_string(),
+            image_url: Some("/uploads/cover.jpg".to_string()),
+            categories: vec!["Society & Culture > Documentary".to_string()],
+            guid: Some(uuid::Uuid::nil()),
+            ..Default::default()
+        };
+        let published = Utc.with_ymd_and_hms(2024, 5, 2, 8, 0, 0).unwrap();
+        let mut episode = post("ep-1", published);
+        episode.episode = Some(EpisodeData {
+            url: "/podcast/audio/ep-1.mp3".to_string(),
+            mime_type: "audio/mpeg".to_string(),
+            file_size: 1234,
+            duration_seconds: 3725,
+            duration: "1:02:05".to_string(),
+            season: Some(2),
+            episode_number: Some(7),
+            episode_type: "bonus".to_string(),
+            explicit: None,
+            transcript_url: Some("/uploads/ep-1.vtt".to_string()),
+        });
+        let posts = vec![episode, post("plain", published)];
+
+        let rss = render_podcast_rss(&channel, &show, &posts);
+        assert!(rss.contains("xmlns:itunes=\"http://www.itunes.com/dtds/podcast-1.0.dtd\""));
+        assert!(rss.contains("<itunes:image href=\"https://example.com/uploads/cover.jpg\"/>"));
+        assert!(rss.contains(
+            "<itunes:category text=\"Society &amp; Culture\"><itunes:category text=\"Documentary\"/></itunes:category>"
+        ));
+        assert!(rss.contains("<podcast:guid>00000000-0000-0000-0000-000000000000</podcast:guid>"));
+        assert!(rss.contains(
+            "<enclosure url=\"https://example.com/podcast/audio/ep-1.mp3\" length=\"1234\" type=\"audio/mpeg\"/>"
+        ));
+        assert!(rss.contains("<guid isPermaLink=\"false\">ep-1</guid>"));
+        assert!(rss.contains("<itunes:duration>3725</itunes:duration>"));
+        assert!(rss.contains("<itunes:episodeType>bonus</itunes:episodeType>"));
+        assert!(rss.contains("type=\"text/vtt\""));
+        // Only episodes are listed
+        assert_eq!(rss.matches("<item>").count(), 1);
+
+        // The other feeds carry the enclosure too
+        let atom = render_atom(&channel, &posts);
+        assert!(atom.contains("rel=\"enclosure\" type=\"audio/mpeg\" length=\"1234\""));
+        let json: serde_json::Value =
+            serde_json::from_str(&render_json_feed(&channel, &posts)).unwrap();
+        assert_eq!(json["items"][0]["attachments"][0]["size_in_bytes"], 1234);
+        assert!(json["items"][1].get("attachments").is_none());
+    }
+
+    #[test]
+    fn test_conditional_get() {
+        let modified = Utc.with_ymd_and_hms(2024, 5, 2, 8, 0, 0).unwrap();
+        let validators = FeedValidators::new("<rss/>", Some(modified));
+        assert_eq!(
+            validators.last_modified_header().as_deref(),
+            Some("Thu, 02 May 2024 08:00:00 GMT")
+        );
+
+        let etag = validators.etag.clone();
+        assert!(validators.not_modified(Some(&etag), None));
+        assert!(validators.not_modified(Some(&format!("\"x\", W/{}", etag)), None));
+        assert!(!validators.not_modified(Some("\"other\""), None));
+        // Entity tags win over dates
+        assert!(!validators.not_modified(Some("\"other\""), Some("Fri, 03 May 2024 00:00:00 GMT")));
+
+        assert!(validators.not_modified(None, Some("Thu, 02 May 2024 08:00:00 GMT")));
+        assert!(!validators.not_modified(None, Some("Wed, 01 May 2024 08:00:00 GMT")));
+        assert!(!validators.not_modified(None, Some("yesterday")));
+        assert!(!FeedValidators::new("", None)
+            .not_modified(None, Some("Thu, 02 May 2024 08:00:00 GMT")));
+    }
+
+    #[test]
+    fn test_xml_escape() {
+        assert_eq!(
+            xml_escape("Tom & \"Jerry\" <3"),
+            "Tom &amp; &quot;Jerry&quot; &lt;3"
+        );
+    }
+}
@@ -0,0 +1,867 @@
+//! GeoIP Enrichment Service
+//!
+//! Resolves client IPs to country, region, city and ASN using a local
+//! MaxMind or DB-IP database in MMDB format, so no request ever leaves the
+//! server for a lookup. The database is refreshed by a scheduled job.
+//!
+//! Lookups feed first-party analytics, the security audit log, per-country
+//! rate limits and country/ASN rules in the IP filter. What ends up stored
+//! is governed by [`GeoIpPrivacy`]: client IPs can be truncated before they
+//! are written and location storage can be reduced to country level or
+//! turned off entirely.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Datelike, TimeZone, Utc};
+use maxminddb::{geoip2, MaxMindDBError, Reader};
+use parking_lot::RwLock;
+use rustpress_auth::GeoLookup;
+use rustpress_core::error::{Error, Result};
+use rustpress_core::http::HttpClient;
+use rustpress_jobs::{JobHandler, JobPayload};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::io::Read;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::json_setting::JsonSetting;
+
+/// Settings key holding the GeoIP configuration
+pub const GEOIP_SETTINGS_KEY: &str = "geoip_config";
+
+/// Stored GeoIP settings
+const GEOIP_SETTING: JsonSetting<GeoIpConfig> =
+    JsonSetting::new(GEOIP_SETTINGS_KEY, "privacy", "GeoIP settings");
+
+/// Placeholder returned in place of the stored license key
+const MASKED_LICENSE_KEY: &str = "********";
+
+/// Largest compressed download accepted (MaxMind City is ~40MB)
+const MAX_DOWNLOAD_SIZE: usize = 256 * 1024 * 1024;
+
+/// Where database files come from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GeoIpProvider {
+    /// DB-IP Lite databases, free and without a license key
+    #[default]
+    DbIp,
+    /// MaxMind GeoLite2 or GeoIP2, needs a license key
+    MaxMind,
+}
+
+impl GeoIpProvider {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::DbIp => "db_ip",
+            Self::MaxMind => "max_mind",
+        }
+    }
+}
+
+/// The two database files the service reads
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GeoDatabaseKind {
+    City,
+    Asn,
+}
+
+/// What is kept when a location is stored
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GeoIpPrivacy {
+    /// Truncate client IPs before they are stored (while GeoIP is enabled)
+    pub truncate_ips: bool,
+    /// Bits of an IPv4 address kept when truncating
+    pub ipv4_prefix: u8,
+    /// Bits of an IPv6 address kept when truncating
+    pub ipv6_prefix: u8,
+    /// Store locations with analytics and audit records at all
+    pub store_location: bool,
+    /// Store city, region and coordinates rather than only the country
+    pub store_city: bool,
+}
+
+impl Default for GeoIpPrivacy {
+    fn default() -> Self {
+        Self {
+            truncate_ips: true,
+            ipv4_prefix: 24,
+            ipv6_prefix: 48,
+            store_location: true,
+            store_city: false,
+        }
+    }
+}
+
+/// GeoIP configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GeoIpConfig {
+    pub enabled: bool,
+    pub provider: GeoIpProvider,
+    /// City (or country) database file
+    pub database_path: PathBuf,
+    /// Optional ASN database file
+    pub asn_database_path: Option<PathBuf>,
+    /// MaxMind license key
+    pub license_key: String,
+    /// MaxMind edition of the city database
+    pub edition: String,
+    /// Download new databases on a schedule
+    pub auto_update: bool,
+    /// Days before a database is considered stale
+    pub update_interval_days: u32,
+    pub privacy: GeoIpPrivacy,
+}
+
+impl Default for GeoIpConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            provider: GeoIpProvider::DbIp,
+            database_path: PathBuf::from("./data/geoip/city.mmdb"),
+            asn_database_path: None,
+            license_key: String::new(),
+            edition: "GeoLite2-City".to_string(),
+            auto_update: true,
+            update_interval_days: 7,
+            privacy: GeoIpPrivacy::default(),
+        }
+    }
+}
+
+impl GeoIpConfig {
+    /// Load the configuration from settings, falling back to defaults
+    pub async fn load(pool: &PgPool) -> Result<Self> {
+        GEOIP_SETTING.load_or_default(pool).await
+    }
+
+    /// Persist the configuration to settings
+    pub async fn save(&self, pool: &PgPool) -> Result<()> {
+        GEOIP_SETTING.save(pool, self).await
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.database_path.as_os_str().is_empty() {
+            return Err(Error::invalid_input(
+                "database_path",
+                "A database path is required",
+            ));
+        }
+        if self.provider == GeoIpProvider::MaxMind
+            && self.auto_update
+            && self.license_key.is_empty()
+        {
+            return Err(Error::invalid_input(
+                "license_key",
+                "MaxMind downloads need a license key",
+            ));
+        }
+        if self.update_interval_days == 0 {
+            return Err(Error::invalid_input(
+                "update_interval_days",
+                "Update interval must be at least one day",
+            ));
+        }
+        if self.privacy.ipv4_prefix > 32 || self.privacy.ipv6_prefix > 128 {
+            return Err(Error::invalid_input(
+                "privacy",
+                "Prefix lengths must be at most 32 (IPv4) and 128 (IPv6)",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Copy safe to return from the API
+    pub fn masked(&self) -> Self {
+        let mut config = self.clone();
+        if !config.license_key.is_empty() {
+            config.license_key = MASKED_LICENSE_KEY.to_string();
+        }
+        config
+    }
+
+    /// Keep the stored license key when an update sends the mask back unchanged
+    pub fn merge_secret(&mut self, current: &GeoIpConfig) {
+        if self.license_key == MASKED_LICENSE_KEY {
+            self.license_key = current.license_key.clone();
+        }
+    }
+
+    /// Path of a database file, if that database is configured
+    pub fn path(&self, kind: GeoDatabaseKind) -> Option<&Path> {
+        match kind {
+            GeoDatabaseKind::City => Some(&self.database_path),
+            GeoDatabaseKind::Asn => self.asn_database_path.as_deref(),
+        }
+    }
+
+    /// Download URL of a database for the configured provider
+    pub fn download_url(&self, kind: GeoDatabaseKind, now: DateTime<Utc>) -> String {
+        match self.provider {
+            GeoIpProvider::DbIp => {
+                let name = match kind {
+                    GeoDatabaseKind::City => "city",
+                    GeoDatabaseKind::Asn => "asn",
+                };
+                format!(
+                    "https://download.db-ip.com/free/dbip-{}-lite-{}-{:02}.mmdb.gz",
+                    name,
+                    now.year(),
+                    now.month()
+                )
+            }
+            GeoIpProvider::MaxMind => {
+                let edition = match kind {
+                    GeoDatabaseKind::City => self.edition.as_str(),
+                    GeoDatabaseKind::Asn => "GeoLite2-ASN",
+                };
+                format!(
+                    "https://download.maxmind.com/app/geoip_download?edition_id={}&license_key={}&suffix=tar.gz",
+                    edition,
+                    urlencoding::encode(&self.license_key)
+                )
+            }
+        }
+    }
+}
+
+/// Location of an IP address
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GeoLocation {
+    /// ISO 3166-1 alpha-2 country code
+    pub country_code: Option<String>,
+    pub country_name: Option<String>,
+    pub continent_code: Option<String>,
+    /// Whether the country is in the European Union
+    pub in_eu: bool,
+    pub region: Option<String>,
+    pub city: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub time_zone: Option<String>,
+    pub asn: Option<u32>,
+    pub asn_organization: Option<String>,
+}
+
+impl GeoLocation {
+    fn from_city(record: &geoip2::City<'_>) -> Self {
+        let english = |names: &Option<std::collections::BTreeMap<&str, &str>>| {
+            names
+                .as_ref()
+                .and_then(|names| names.get("en"))
+                .map(|name| name.to_string())
+        };
+        let country = record.country.as_ref();
+        let location = record.location.as_ref();
+
+        Self {
+            country_code: country.and_then(|c| c.iso_code).map(str::to_string),
+            country_name: country.and_then(|c| english(&c.names)),
+            continent_code: record
+                .continent
+                .as_ref()
+                .and_then(|c| c.code)
+                .map(str::to_string),
+            in_eu: country
+                .and_then(|c| c.is_in_european_union)
+                .unwrap_or(false),
+            region: record
+                .subdivisions
+                .as_ref()
+                .and_then(|subdivisions| subdivisions.first())
+                .and_then(|s| english(&s.names)),
+            city: record.city.as_ref().and_then(|c| english(&c.names)),
+            latitude: location.and_then(|l| l.latitude),
+            longitude: location.and_then(|l| l.longitude),
+            time_zone: location.and_then(|l| l.time_zone).map(str::to_string),
+            asn: None,
+            asn_organization: None,
+        }
+    }
+
+    /// Reduce to what the privacy settings allow to be stored
+    pub fn for_storage(&self, privacy: &GeoIpPrivacy) -> Option<Self> {
+        if !privacy.store_location {
+            return None;
+        }
+        if privacy.store_city {
+            return Some(self.clone());
+        }
+        Some(Self {
+            country_code: self.country_code.clone(),
+            country_name: self.country_name.clone(),
+            continent_code: self.continent_code.clone(),
+            in_eu: self.in_eu,
+            ..Default::default()
+        })
+    }
+}
+
+/// Zero the host bits of an address, keeping `ipv4_prefix`/`ipv6_prefix` bits
+pub fn truncate_ip(ip: IpAddr, ipv4_prefix: u8, ipv6_prefix: u8) -> IpAddr {
+    match ip {
+        IpAddr::V4(v4) => {
+            let prefix = ipv4_prefix.min(32) as u32;
+            let mask = u32::MAX.checked_shl(32 - prefix).unwrap_or(0);
+            IpAddr::V4(Ipv4Addr::from(u32::from(v4) & mask))
+        }
+        IpAddr::V6(v6) => {
+            let prefix = ipv6_prefix.min(128) as u32;
+            let mask = u128::MAX.checked_shl(128 - prefix).unwrap_or(0);
+            IpAddr::V6(Ipv6Addr::from(u128::from(v6) & mask))
+        }
+    }
+}
+
+/// A loaded database file
+#[derive(Debug, Clone, Serialize)]
+pub struct GeoDatabaseInfo {
+    pub path: PathBuf,
+    pub database_type: String,
+    pub built_at: Option<DateTime<Utc>>,
+    pub ip_version: u16,
+    pub node_count: u32,
+}
+
+/// Service status for the admin API
+#[derive(Debug, Clone, Serialize)]
+pub struct GeoIpStatus {
+    pub enabled: bool,
+    pub provider: GeoIpProvider,
+    pub city_database: Option<GeoDatabaseInfo>,
+    pub asn_database: Option<GeoDatabaseInfo>,
+    pub last_update: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
+
+struct LoadedDatabase {
+    reader: Reader<Vec<u8>>,
+    info: GeoDatabaseInfo,
+}
+
+impl LoadedDatabase {
+    fn open(path: &Path, bytes: Vec<u8>) -> Result<Self> {
+        let reader = Reader::from_source(bytes).map_err(|e| {
+            Error::validation(format!(
+                "{} is not a valid MMDB file: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        let info = GeoDatabaseInfo {
+            path: path.to_path_buf(),
+            database_type: reader.metadata.database_type.clone(),
+            built_at: Utc
+                .timestamp_opt(reader.metadata.build_epoch as i64, 0)
+                .single(),
+            ip_version: reader.metadata.ip_version,
+            node_count: reader.metadata.node_count,
+        };
+        Ok(Self { reader, info })
+    }
+}
+
+/// Local GeoIP database lookups.
+///
+/// Lookups are synchronous so they can run inside the audit logger and
+/// other non-async paths; call [`GeoIpService::load`] once at startup.
+pub struct GeoIpService {
+    pool: PgPool,
+    client: HttpClient,
+    config: RwLock<Arc<GeoIpConfig>>,
+    city: RwLock<Option<Arc<LoadedDatabase>>>,
+    asn: RwLock<Option<Arc<LoadedDatabase>>>,
+    last_update: RwLock<Option<DateTime<Utc>>>,
+    last_error: RwLock<Option<String>>,
+    update_lock: tokio::sync::Mutex<()>,
+}
+
+impl GeoIpService {
+    pub fn new(pool: PgPool, client: HttpClient) -> Self {
+        Self {
+            pool,
+            client,
+            config: RwLock::new(Arc::new(GeoIpConfig::default())),
+            city: RwLock::new(None),
+            asn: RwLock::new(None),
+            last_update: RwLock::new(None),
+            last_error: RwLock::new(None),
+            update_lock: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Load the configuration from settings and open the database files
+    pub async fn load(&self) -> Result<()> {
+        let config = GeoIpConfig::load(&self.pool).await?;
+        self.set_config(config).await
+    }
+
+    pub fn config(&self) -> Arc<GeoIpConfig> {
+        self.config.read().clone()
+    }
+
+    /// Apply a saved configuration and reopen the database files
+    pub async fn set_config(&self, config: GeoIpConfig) -> Result<()> {
+        let config = Arc::new(config);
+        *self.config.write() = config.clone();
+
+        if !config.enabled {
+            *self.city.write() = None;
+            *self.asn.write() = None;
+            return Ok(());
+        }
+
+        for kind in [GeoDatabaseKind::City, GeoDatabaseKind::Asn] {
+            let database = match config.path(kind) {
+                Some(path) => open_database(path).await,
+                None => Ok(None),
+            };
+            match database {
+                Ok(database) => *self.slot(kind).write() = database.map(Arc::new),
+                Err(e) => {
+                    tracing::warn!("Failed to open GeoIP database: {}", e);
+                    *self.last_error.write() = Some(e.to_string());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn slot(&self, kind: GeoDatabaseKind) -> &RwLock<Option<Arc<LoadedDatabase>>> {
+        match kind {
+            GeoDatabaseKind::City => &self.city,
+            GeoDatabaseKind::Asn => &self.asn,
+        }
+    }
+
+    /// Whether a city database is loaded
+    pub fn is_available(&self) -> bool {
+        self.city.read().is_some()
+    }
+
+    /// Full location of an address
+    pub fn lookup(&self, ip: IpAddr) -> Option<GeoLocation> {
+        let city = self.city.read().clone();
+        let asn = self.asn.read().clone();
+        if city.is_none() && asn.is_none() {
+            return None;
+        }
+
+        let mut location = city
+            .and_then(|db| {
+                lookup_record::<geoip2::City<'_>>(&db.reader, ip)
+                    .map(|r| GeoLocation::from_city(&r))
+            })
+            .unwrap_or_default();
+        if let Some(record) = asn
+            .as_ref()
+            .and_then(|db| lookup_record::<geoip2::Asn<'_>>(&db.reader, ip))
+        {
+            location.asn = record.autonomous_system_number;
+            location.asn_organization = record.autonomous_system_organization.map(str::to_string);
+        }
+
+        (location != GeoLocation::default()).then_some(location)
+    }
+
+    /// Location of an address reduced to what may be stored
+    pub fn lookup_for_storage(&self, ip: IpAddr) -> Option<GeoLocation> {
+        let config = self.config();
+        if !config.privacy.store_location {
+            return None;
+        }
+        self.lookup(ip)?.for_storage(&config.privacy)
+    }
+
+    /// Country code to store with a record, if storage is allowed
+    pub fn country_for_storage(&self, ip: IpAddr) -> Option<String> {
+        self.lookup_for_storage(ip)?.country_code
+    }
+
+    /// Client IP as it may be stored
+    pub fn storage_ip(&self, ip: IpAddr) -> IpAddr {
+        let config = self.config();
+        let privacy = &config.privacy;
+        if config.enabled && privacy.truncate_ips {
+            truncate_ip(ip, privacy.ipv4_prefix, privacy.ipv6_prefix)
+        } else {
+            ip
+        }
+    }
+
+    pub fn status(&self) -> GeoIpStatus {
+        let config = self.config();
+        GeoIpStatus {
+            enabled: config.enabled,
+            provider: config.provider,
+            city_database: self.city.read().as_ref().map(|db| db.info.clone()),
+            asn_database: self.asn.read().as_ref().map(|db| db.info.clone()),
+            last_update: *self.last_update.read(),
+            last_error: self.last_error.read().clone(),
+        }
+    }
+
+    /// Whether any configured database is missing or older than the interval
+    pub async fn needs_update(&self) -> bool {
+        let config = self.config();
+        let max_age = Duration::from_secs(u64::from(config.update_interval_days) * 86400);
+
+        for kind in [GeoDatabaseKind::City, GeoDatabaseKind::Asn] {
+            let Some(path) = config.path(kind) else {
+                continue;
+            };
+            let age = tokio::fs::metadata(path)
+                .await
+                .and_then(|meta| meta.modified())
+                .ok()
+                .and_then(|modified| modified.elapsed().ok());
+            match age {
+                Some(age) if age < max_age => {}
+                _ => return true,
+            }
+        }
+        false
+    }
+
+    /// Download fresh databases and swap them in
+    pub async fn update_databases(&self) -> Result<Vec<GeoDatabaseInfo>> {
+        let _guard = self.update_lock.lock().await;
+        let config = self.config();
+        config.validate()?;
+
+        let mut updated = Vec::new();
+        for kind in [GeoDatabaseKind::City, GeoDatabaseKind::Asn] {
+            let Some(path) = config.path(kind) else {
+                continue;
+            };
+            match self.download(&config, kind, path).await {
+                Ok(database) => {
+                    updated.push(database.info.clone());
+                    *self.slot(kind).write() = Some(Arc::new(database));
+                }
+                Err(e) => {
+                    *self.last_error.write() = Some(e.to_string());
+                    return Err(e);
+                }
+            }
+        }
+
+        *self.last_update.write() = Some(Utc::now());
+        *self.last_error.write() = None;
+        Ok(updated)
+    }
+
+    async fn download(
+        &self,
+        config: &GeoIpConfig,
+        kind: GeoDatabaseKind,
+        path: &Path,
+    ) -> Result<LoadedDatabase> {
+        let url = config.download_url(kind, Utc::now());
+        tracing::info!(
+            provider = config.provider.as_str(),
+            ?kind,
+            "Downloading GeoIP database"
+        );
+
+        let download_error = |e: reqwest::Error| {
+            Error::internal(format!("GeoIP download failed: {}", e.without_url()))
+        };
+        // Download links redirect to the provider's storage
+        let client = self.client.following_redirects();
+        let mut response = client
+            .send(client.get(&url).timeout(Duration::from_secs(300)))
+            .await
+            .map_err(|e| Error::internal(format!("GeoIP download failed: {}", e)))?
+            .error_for_status()
+            .map_err(download_error)?;
+        if response
+            .content_length()
+            .is_some_and(|len| len > MAX_DOWNLOAD_SIZE as u64)
+        {
+            return Err(Error::validation("GeoIP download is too large"));
+        }
+
+        // The length header is optional, so enforce the limit while streaming
+        let mut archive = Vec::new();
+        while let Some(chunk) = response.chunk().await.map_err(download_error)? {
+            if archive.len() + chunk.len() > MAX_DOWNLOAD_SIZE {
+                return Err(Error::validation("GeoIP download is too large"));
+            }
+            archive.extend_from_slice(&chunk);
+        }
+
+        let provider = config.provider;
+        let path = path.to_path_buf();
+        tokio::task::spawn_blocking(move || {
+            let bytes = extract_mmdb(provider, &archive)?;
+            install_database(&path, bytes)
+        })
+        .await
+        .map_err(|e| Error::internal(format!("GeoIP extraction failed: {}", e)))?
+    }
+}
+
+impl GeoLookup for GeoIpService {
+    fn country_code(&self, ip: &IpAddr) -> Option<String> {
+        self.lookup(*ip)?.country_code
+    }
+
+    fn asn(&self, ip: &IpAddr) -> Option<u32> {
+        self.lookup(*ip)?.asn
+    }
+}
+
+fn lookup_record<'a, T: Deserialize<'a>>(reader: &'a Reader<Vec<u8>>, ip: IpAddr) -> Option<T> {
+    match reader.lookup::<T>(ip) {
+        Ok(record) => Some(record),
+        Err(MaxMindDBError::AddressNotFoundError(_)) => None,
+        Err(e) => {
+            tracing::debug!(%ip, "GeoIP lookup failed: {}", e);
+            None
+        }
+    }
+}
+
+async fn open_database(path: &Path) -> Result<Option<LoadedDatabase>> {
+    let bytes = match tokio::fs::read(path).await {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            tracing::info!(path = %path.display(), "GeoIP database not downloaded yet");
+            return Ok(None);
+        }
+        Err(e) => {
+            return Err(Error::internal(format!(
+                "Failed to read {}: {}",
+                path.display(),
+                e
+            )))
+        }
+    };
+    LoadedDatabase::open(path, bytes).map(Some)
+}
+
+/// Unpack the MMDB file from a provider download
+fn extract_mmdb(provider: GeoIpProvider, archive: &[u8]) -> Result<Vec<u8>> {
+    let mut decoded = Vec::new();
+    flate2::read::GzDecoder::new(archive)
+        .take(MAX_DOWNLOAD_SIZE as u64)
+        .read_to_end(&mut decoded)
+        .map_err(|e| Error::validation(format!("GeoIP download is not gzip data: {}", e)))?;
+
+    match provider {
+        GeoIpProvider::DbIp => Ok(decoded),
+        GeoIpProvider::MaxMind => find_tar_entry(&decoded, ".mmdb")
+            .map(<[u8]>::to_vec)
+            .ok_or_else(|| Error::validation("GeoIP archive contains no .mmdb file")),
+    }
+}
+
+/// Contents of the first tar entry whose name ends with `suffix`
+fn find_tar_entry<'a>(tar: &'a [u8], suffix: &str) -> Option<&'a [u8]> {
+    const BLOCK: usize = 512;
+    let mut offset = 0;
+
+    while offset + BLOCK <= tar.len() {
+        let header = &tar[offset..offset + BLOCK];
+        if header.iter().all(|b| *b == 0) {
+            return None;
+        }
+        let name_end = header[..100].iter().position(|b| *b == 0).unwrap_or(100);
+        let name = std::str::from_utf8(&header[..name_end]).ok()?;
+        let size = std::str::from_utf8(&header[124..136])
+            .ok()
+            .map(|s| s.trim_matches(|c: char| c == '\0' || c.is_whitespace()))
+            .and_then(|s| usize::from_str_radix(s, 8).ok())?;
+
+        let start = offset + BLOCK;
+        let end = start.checked_add(size)?;
+        if end > tar.len() {
+            return None;
+        }
+        if name.ends_with(suffix) {
+            return Some(&tar[start..end]);
+        }
+        offset = start + size.div_ceil(BLOCK) * BLOCK;
+    }
+    None
+}
+
+/// Validate a downloaded database and move it into place
+fn install_database(path: &Path, bytes: Vec<u8>) -> Result<LoadedDatabase> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| {
+            Error::internal(format!("Failed to create {}: {}", parent.display(), e))
+        })?;
+    }
+    let temp = path.with_extension("mmdb.tmp");
+    std::fs::write(&temp, &bytes)
+        .map_err(|e| Error::internal(format!("Failed to write {}: {}", temp.display(), e)))?;
+
+    // The reader takes ownership of the bytes; only replace the old file
+    // once they have been accepted as a valid database
+    let database = match LoadedDatabase::open(path, bytes) {
+        Ok(database) => database,
+        Err(e) => {
+            let _ = std::fs::remove_file(&temp);
+            return Err(e);
+        }
+    };
+    std::fs::rename(&temp, path)
+        .map_err(|e| Error::internal(format!("Failed to replace {}: {}", path.display(), e)))?;
+    Ok(database)
+}
+
+/// Scheduled GeoIP database refresh
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UpdateGeoIpDatabaseJob {
+    /// Download even if the files are not stale yet
+    pub force: bool,
+}
+
+impl JobPayload for UpdateGeoIpDatabaseJob {
+    fn job_type() -> &'static str {
+        "update_geoip_database"
+    }
+
+    fn queue() -> &'static str {
+        "maintenance"
+    }
+
+    fn timeout_secs() -> u64 {
+        900
+    }
+}
+
+/// Handler for [`UpdateGeoIpDatabaseJob`]
+#[derive(Clone)]
+pub struct UpdateGeoIpDatabaseHandler {
+    geoip: Arc<GeoIpService>,
+}
+
+impl UpdateGeoIpDatabaseHandler {
+    pub fn new(geoip: Arc<GeoIpService>) -> Self {
+        Self { geoip }
+    }
+}
+
+#[async_trait]
+impl JobHandler for UpdateGeoIpDatabaseHandler {
+    type Payload = UpdateGeoIpDatabaseJob;
+
+    async fn handle(&self, payload: Self::Payload) -> Result<()> {
+        let config = self.geoip.config();
+        if !config.enabled || !(payload.force || config.auto_update) {
+            return Ok(());
+        }
+        if !payload.force && !self.geoip.needs_update().await {
+            return Ok(());
+        }
+
+        let updated = self.geoip.update_databases().await?;
+        tracing::info!(databases = updated.len(), "GeoIP databases updated");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_ip() {
+        let ip = |s: &str| s.parse::<IpAddr>().unwrap();
+        assert_eq!(truncate_ip(ip("203.0.113.77"), 24, 48), ip("203.0.113.0"));
+        assert_eq!(truncate_ip(ip("203.0.113.77"), 16, 48), ip("203.0.0.0"));
+        assert_eq!(truncate_ip(ip("203.0.113.77"), 0, 48), ip("0.0.0.0"));
+        assert_eq!(truncate_ip(ip("203.0.113.77"), 32, 48), ip("203.0.113.77"));
+        assert_eq!(
+            truncate_ip(ip("2001:db8:85a3:8d3:1319:8a2e:370:7348"), 24, 48),
+            ip("2001:db8:85a3::")
+        );
+    }
+
+    #[test]
+    fn test_location_storage_privacy() {
+        let location = GeoLocation {
+            country_code: Some("DE".to_string()),
+            in_eu: true,
+            city: Some("Berlin".to_string()),
+            latitude: Some(52.52),
+            longitude: Some(13.4),
+            asn: Some(3320),
+            ..Default::default()
+        };
+
+        let stored = location.for_storage(&GeoIpPrivacy::default()).unwrap();
+        assert_eq!(stored.country_code.as_deref(), Some("DE"));
+        assert!(stored.in_eu);
+        assert!(stored.city.is_none() && stored.latitude.is_none() && stored.asn.is_none());
+
+        let full = GeoIpPrivacy {
+            store_city: true,
+            ..Default::default()
+        };
+        assert_eq!(location.for_storage(&full).unwrap(), location);
+
+        let opted_out = GeoIpPrivacy {
+            store_location: false,
+            ..Default::default()
+        };
+        assert!(location.for_storage(&opted_out).is_none());
+    }
+
+    #[test]
+    fn test_config_validation_and_urls() {
+        let now = Utc.with_ymd_and_hms(2024, 3, 5, 0, 0, 0).unwrap();
+        let mut config = GeoIpConfig::default();
+        assert!(config.validate().is_ok());
+        assert_eq!(
+            config.download_url(GeoDatabaseKind::City, now),
+            "https://download.db-ip.com/free/dbip-city-lite-2024-03.mmdb.gz"
+        );
+
+        config.provider = GeoIpProvider::MaxMind;
+        assert!(config.validate().is_err());
+        config.license_key = "key&1".to_string();
+        assert!(config.validate().is_ok());
+        assert!(config
+            .download_url(GeoDatabaseKind::Asn, now)
+            .contains("edition_id=GeoLite2-ASN&license_key=key%261"));
+
+        let masked = config.masked();
+        assert_eq!(masked.license_key, MASKED_LICENSE_KEY);
+        let mut update = masked.clone();
+        update.merge_secret(&config);
+        assert_eq!(update.license_key, "key&1");
+
+        config.privacy.ipv4_prefix = 40;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_find_tar_entry() {
+        fn entry(name: &str, data: &[u8]) -> Vec<u8> {
+            let mut header = vec![0u8; 512];
+            header[..name.len()].copy_from_slice(name.as_bytes());
+            let size = format!("{:011o}\0", data.len());
+            header[124..136].copy_from_slice(size.as_bytes());
+            let mut block = header;
+            block.extend_from_slice(data);
+            block.resize(512 + data.len().div_ceil(512) * 512, 0);
+            block
+        }
+
+        let mut tar = entry("GeoLite2-City_20240305/LICENSE.txt", &[b'x'; 600]);
+        tar.extend(entry("GeoLite2-City_20240305/GeoLite2-City.mmdb", b"mmdb"));
+        tar.extend(vec![0u8; 1024]);
+
+        assert_eq!(find_tar_entry(&tar, ".mmdb"), Some(&b"mmdb"[..]));
+        assert_eq!(find_tar_entry(&tar, ".csv"), None);
+    }
+}
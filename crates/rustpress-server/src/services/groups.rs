@@ -0,0 +1,659 @@
+//! User Groups
+//!
+//! Teams layered on top of roles. A group can:
+//!
+//! - grant roles and `resource:action` capabilities, which its members
+//!   hold in addition to their own role
+//! - own content sections: categories, together with their subcategories
+//!   (e.g. the marketing team owns `/blog/marketing`). Posts filed in an
+//!   owned section can only be changed by members of an owning group and
+//!   by administrators; posts in no owned section are unaffected
+//! - be notified as a whole, live and optionally by email
+//!
+//! Managers of a group may add and remove its members and notify it;
+//! everything else is for administrators and holders of `groups:manage`.
+//! Only administrators may have a group grant, or add members to a group
+//! granting, roles and capabilities they don't hold themselves.
+//! What a user gains from their groups is cached for
+//! [`GRANTS_CACHE_TTL`], so changes made on another node take at most that
+//! long to apply.
+
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use rustpress_auth::{Permission, PermissionChecker};
+use rustpress_core::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use sqlx::{FromRow, PgPool};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// How long a user's group grants are reused before being reloaded
+pub const GRANTS_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// Longest accepted group name
+const MAX_NAME_LENGTH: usize = 100;
+
+/// Most roles or capabilities a group can grant
+const MAX_GRANTS: usize = 32;
+
+/// Most users added to a group in one request
+pub const MAX_MEMBERS_PER_REQUEST: usize = 500;
+
+/// Deepest category nesting followed when looking for a section's owner
+const MAX_SECTION_DEPTH: i32 = 32;
+
+const GROUP_COLUMNS: &str = "g.id, g.name, g.slug, g.description, g.roles, g.capabilities, \
+     (SELECT COUNT(*) FROM user_group_members m WHERE m.group_id = g.id) AS member_count, \
+     (SELECT COUNT(*) FROM user_group_sections s WHERE s.group_id = g.id) AS section_count, \
+     g.created_at, g.updated_at";
+
+/// A group of users
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct UserGroup {
+    pub id: Uuid,
+    pub name: String,
+    pub slug: String,
+    pub description: Option<String>,
+    /// Roles every member holds in addition to their own
+    pub roles: Json<Vec<String>>,
+    /// `resource:action` pairs every member holds, `*` matching any
+    pub capabilities: Json<Vec<String>>,
+    pub member_count: i64,
+    pub section_count: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A group as submitted
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct GroupInput {
+    pub name: String,
+    /// Derived from the name when absent
+    #[serde(default)]
+    pub slug: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub roles: Vec<String>,
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+}
+
+impl GroupInput {
+    /// Trim and check the input; roles must be known to `permissions`
+    pub fn normalize(mut self, permissions: &PermissionChecker) -> Result<Self> {
+        self.name = self.name.trim().to_string();
+        if self.name.is_empty() {
+            return Err(Error::invalid_input("name", "Name is required"));
+        }
+        if self.name.chars().count() > MAX_NAME_LENGTH {
+            return Err(Error::invalid_input(
+                "name",
+                format!("Must be at most {} characters", MAX_NAME_LENGTH),
+            ));
+        }
+
+        let slug = match self.slug.as_deref().map(str::trim) {
+            Some(slug) if !slug.is_empty() => {
+                slugify::slugify(slug, "", "-", Some(MAX_NAME_LENGTH))
+            }
+            _ => slugify::slugify(&self.name, "", "-", Some(MAX_NAME_LENGTH)),
+        };
+        if slug.is_empty() {
+            return Err(Error::invalid_input(
+                "slug",
+                "Must contain at least one letter or digit",
+            ));
+        }
+        self.slug = Some(slug);
+
+        self.description = self
+            .description
+            .map(|d| d.trim().to_string())
+            .filter(|d| !d.is_empty());
+
+        if self.roles.len() > MAX_GRANTS || self.capabilities.len() > MAX_GRANTS {
+            return Err(Error::invalid_input(
+                "roles",
+                format!(
+                    "At most {} roles and {} capabilities may be granted",
+                    MAX_GRANTS, MAX_GRANTS
+                ),
+            ));
+        }
+
+        let mut roles: Vec<String> = self.roles.iter().map(|r| r.trim().to_string()).collect();
+        if let Some(unknown) = roles.iter().find(|r| permissions.get_role(r).is_none()) {
+            return Err(Error::invalid_input(
+                "roles",
+                format!("Unknown role '{}'", unknown),
+            ));
+        }
+        roles.sort();
+        roles.dedup();
+        self.roles = roles;
+
+        let mut capabilities = Vec::with_capacity(self.capabilities.len());
+        for capability in &self.capabilities {
+            let permission = Permission::from_str(capability.trim())
+                .ok()
+                .filter(|p| !p.resource.is_empty() && !p.action.is_empty())
+                .ok_or_else(|| {
+                    Error::invalid_input(
+                        "capabilities",
+                        format!("'{}' is not a resource:action pair", capability),
+                    )
+                })?;
+            capabilities.push(permission.to_string());
+        }
+        capabilities.sort();
+        capabilities.dedup();
+        self.capabilities = capabilities;
+
+        Ok(self)
+    }
+}
+
+/// A member of a group
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct GroupMember {
+    pub user_id: Uuid,
+    pub email: String,
+    pub username: String,
+    pub display_name: Option<String>,
+    pub is_manager: bool,
+    pub added_at: DateTime<Utc>,
+}
+
+/// A category owned by a group
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct GroupSection {
+    pub category_id: Uuid,
+    pub name: String,
+    pub slug: String,
+    pub added_at: DateTime<Utc>,
+}
+
+/// A user to notify
+#[derive(Debug, Clone, FromRow)]
+pub struct GroupRecipient {
+    pub user_id: Uuid,
+    pub email: String,
+    pub display_name: Option<String>,
+}
+
+/// What a user gains from the groups they belong to
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GroupGrants {
+    /// Groups the user belongs to
+    pub groups: Vec<Uuid>,
+    /// Groups the user manages
+    pub managed: Vec<Uuid>,
+    pub roles: Vec<String>,
+    pub capabilities: Vec<String>,
+}
+
+impl GroupGrants {
+    pub fn is_member(&self, group_id: Uuid) -> bool {
+        self.groups.contains(&group_id)
+    }
+
+    pub fn manages(&self, group_id: Uuid) -> bool {
+        self.managed.contains(&group_id)
+    }
+
+    /// Whether a granted capability covers `resource:action`
+    pub fn has_capability(&self, resource: &str, action: &str) -> bool {
+        let wanted = Permission::new(resource, action);
+        self.capabilities
+            .iter()
+            .filter_map(|c| Permission::from_str(c).ok())
+            .any(|p| p.covers(&wanted))
+    }
+
+    /// Whether content owned by `owners` may be changed by the user. Content
+    /// in no owned section may be; administrators are checked by callers.
+    pub fn may_change_section(&self, owners: &[Uuid]) -> bool {
+        owners.is_empty() || owners.iter().any(|owner| self.is_member(*owner))
+    }
+}
+
+/// Row of a user's memberships, combined into [`GroupGrants`]
+#[derive(FromRow)]
+struct MembershipRow {
+    group_id: Uuid,
+    is_manager: bool,
+    roles: Json<Vec<String>>,
+    capabilities: Json<Vec<String>>,
+}
+
+fn combine_grants(rows: Vec<MembershipRow>) -> GroupGrants {
+    let mut grants = GroupGrants::default();
+    for row in rows {
+        grants.groups.push(row.group_id);
+        if row.is_manager {
+            grants.managed.push(row.group_id);
+        }
+        grants.roles.extend(row.roles.0);
+        grants.capabilities.extend(row.capabilities.0);
+    }
+    grants.roles.sort();
+    grants.roles.dedup();
+    grants.capabilities.sort();
+    grants.capabilities.dedup();
+    grants
+}
+
+fn unique_violation(e: &sqlx::Error) -> bool {
+    matches!(e, sqlx::Error::Database(db) if db.code().as_deref() == Some("23505"))
+}
+
+/// Groups, their members and the sections they own
+pub struct GroupService {
+    pool: PgPool,
+    grants: Mutex<HashMap<Uuid, (Instant, Arc<GroupGrants>)>>,
+}
+
+impl GroupService {
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            grants: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub async fn list(&self) -> Result<Vec<UserGroup>> {
+        sqlx::query_as(&format!(
+            "SELECT {} FROM user_groups g ORDER BY g.name",
+            GROUP_COLUMNS
+        ))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to list groups", e))
+    }
+
+    /// Groups a user belongs to
+    pub async fn for_user(&self, user_id: Uuid) -> Result<Vec<UserGroup>> {
+        sqlx::query_as(&format!(
+            "SELECT {} FROM user_groups g \
+             JOIN user_group_members gm ON gm.group_id = g.id \
+             WHERE gm.user_id = $1 ORDER BY g.name",
+            GROUP_COLUMNS
+        ))
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to list groups", e))
+    }
+
+    pub async fn get(&self, id: Uuid) -> Result<UserGroup> {
+        sqlx::query_as(&format!(
+            "SELECT {} FROM user_groups g WHERE g.id = $1",
+            GROUP_COLUMNS
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load group", e))?
+        .ok_or_else(|| Error::not_found("Group", id.to_string()))
+    }
+
+    pub async fn create(
+        &self,
+        input: GroupInput,
+        permissions: &PermissionChecker,
+    ) -> Result<UserGroup> {
+        let input = input.normalize(permissions)?;
+        let id = Uuid::now_v7();
+        sqlx::query(
+            "INSERT INTO user_groups (id, name, slug, description, roles, capabilities) \
+             VALUES ($1, $2, $3, $4, $5, $6)",
+        )
+        .bind(id)
+        .bind(&input.name)
+        .bind(&input.slug)
+        .bind(&input.description)
+        .bind(Json(&input.roles))
+        .bind(Json(&input.capabilities))
+        .execute(&self.pool)
+        .await
+        .map_err(|e| {
+            if unique_violation(&e) {
+                Error::invalid_input("slug", "A group with this slug already exists")
+            } else {
+                Error::database_with_source("Failed to create group", e)
+            }
+        })?;
+        self.get(id).await
+    }
+
+    pub async fn update(
+        &self,
+        id: Uuid,
+        input: GroupInput,
+        permissions: &PermissionChecker,
+    ) -> Result<UserGroup> {
+        let input = input.normalize(permissions)?;
+        let updated = sqlx::query(
+            "UPDATE user_groups SET name = $2, slug = $3, description = $4, roles = $5, \
+             capabilities = $6, updated_at = NOW() WHERE id = $1",
+        )
+        .bind(id)
+        .bind(&input.name)
+        .bind(&input.slug)
+        .bind(&input.description)
+        .bind(Json(&input.roles))
+        .bind(Json(&input.capabilities))
+        .execute(&self.pool)
+        .await
+        .map_err(|e| {
+            if unique_violation(&e) {
+                Error::invalid_input("slug", "A group with this slug already exists")
+            } else {
+                Error::database_with_source("Failed to update group", e)
+            }
+        })?;
+        if updated.rows_affected() == 0 {
+            return Err(Error::not_found("Group", id.to_string()));
+        }
+        self.forget_grants();
+        self.get(id).await
+    }
+
+    pub async fn delete(&self, id: Uuid) -> Result<()> {
+        let deleted = sqlx::query("DELETE FROM user_groups WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to delete group", e))?;
+        if deleted.rows_affected() == 0 {
+            return Err(Error::not_found("Group", id.to_string()));
+        }
+        self.forget_grants();
+        Ok(())
+    }
+
+    pub async fn members(&self, id: Uuid) -> Result<Vec<GroupMember>> {
+        self.get(id).await?;
+        sqlx::query_as(
+            "SELECT u.id AS user_id, u.email, u.username, u.display_name, gm.is_manager, \
+             gm.added_at FROM user_group_members gm JOIN users u ON u.id = gm.user_id \
+             WHERE gm.group_id = $1 ORDER BY gm.is_manager DESC, u.username",
+        )
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to list group members", e))
+    }
+
+    /// Add users to a group, or change whether they manage it. Unknown and
+    /// deleted users are ignored. Returns the group's members.
+    pub async fn add_members(
+        &self,
+        id: Uuid,
+        user_ids: &[Uuid],
+        manager: bool,
+    ) -> Result<Vec<GroupMember>> {
+        if user_ids.is_empty() {
+            return Err(Error::invalid_input(
+                "user_ids",
+                "At least one user is required",
+            ));
+        }
+        if user_ids.len() > MAX_MEMBERS_PER_REQUEST {
+            return Err(Error::invalid_input(
+                "user_ids",
+                format!(
+                    "At most {} users may be added at once",
+                    MAX_MEMBERS_PER_REQUEST
+                ),
+            ));
+        }
+        self.get(id).await?;
+
+        sqlx::query(
+            "INSERT INTO user_group_members (group_id, user_id, is_manager) \
+             SELECT $1, u.id, $3 FROM users u WHERE u.id = ANY($2) AND u.deleted_at IS NULL \
+             ON CONFLICT (group_id, user_id) DO UPDATE SET is_manager = EXCLUDED.is_manager",
+        )
+        .bind(id)
+        .bind(user_ids)
+        .bind(manager)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to add group members", e))?;
+
+        self.forget_grants();
+        self.members(id).await
+    }
+
+    pub async fn remove_member(&self, id: Uuid, user_id: Uuid) -> Result<()> {
+        let removed =
+            sqlx::query("DELETE FROM user_group_members WHERE group_id = $1 AND user_id = $2")
+                .bind(id)
+                .bind(user_id)
+                .execute(&self.pool)
+                .await
+                .map_err(|e| Error::database_with_source("Failed to remove group member", e))?;
+        if removed.rows_affected() == 0 {
+            return Err(Error::not_found("Group member", user_id.to_string()));
+        }
+        self.forget_grants();
+        Ok(())
+    }
+
+    pub async fn sections(&self, id: Uuid) -> Result<Vec<GroupSection>> {
+        self.get(id).await?;
+        sqlx::query_as(
+            "SELECT c.id AS category_id, c.name, c.slug, s.added_at \
+             FROM user_group_sections s JOIN categories c ON c.id = s.category_id \
+             WHERE s.group_id = $1 ORDER BY c.name",
+        )
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to list group sections", e))
+    }
+
+    /// Give a group ownership of a category and its subcategories. Returns
+    /// the group's sections.
+    pub async fn add_section(&self, id: Uuid, category_id: Uuid) -> Result<Vec<GroupSection>> {
+        self.get(id).await?;
+        let added = sqlx::query(
+            "INSERT INTO user_group_sections (group_id, category_id) \
+             SELECT $1, c.id FROM categories c WHERE c.id = $2 \
+             ON CONFLICT (group_id, category_id) DO NOTHING",
+        )
+        .bind(id)
+        .bind(category_id)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to add group section", e))?;
+
+        if added.rows_affected() == 0 {
+            let exists: bool =
+                sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM categories WHERE id = $1)")
+                    .bind(category_id)
+                    .fetch_one(&self.pool)
+                    .await
+                    .map_err(|e| Error::database_with_source("Failed to load category", e))?;
+            if !exists {
+                return Err(Error::not_found("Category", category_id.to_string()));
+            }
+        }
+        self.sections(id).await
+    }
+
+    pub async fn remove_section(&self, id: Uuid, category_id: Uuid) -> Result<()> {
+        let removed =
+            sqlx::query("DELETE FROM user_group_sections WHERE group_id = $1 AND category_id = $2")
+                .bind(id)
+                .bind(category_id)
+                .execute(&self.pool)
+                .await
+                .map_err(|e| Error::database_with_source("Failed to remove group section", e))?;
+        if removed.rows_affected() == 0 {
+            return Err(Error::not_found("Group section", category_id.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Groups owning any of the categories or one of their ancestors
+    pub async fn section_owners(&self, category_ids: &[Uuid]) -> Result<Vec<Uuid>> {
+        if category_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        sqlx::query_scalar(
+            "WITH RECURSIVE ancestry (id, parent_id, depth) AS ( \
+                 SELECT id, parent_id, 0 FROM categories WHERE id = ANY($1) \
+                 UNION ALL \
+                 SELECT c.id, c.parent_id, a.depth + 1 FROM categories c \
+                 JOIN ancestry a ON c.id = a.parent_id WHERE a.depth < $2 \
+             ) \
+             SELECT DISTINCT s.group_id FROM user_group_sections s \
+             JOIN ancestry a ON a.id = s.category_id",
+        )
+        .bind(category_ids)
+        .bind(MAX_SECTION_DEPTH)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load section owners", e))
+    }
+
+    /// Groups owning a section a post is filed in
+    pub async fn post_owners(&self, post_id: Uuid) -> Result<Vec<Uuid>> {
+        let category_ids: Vec<Uuid> =
+            sqlx::query_scalar("SELECT category_id FROM post_categories WHERE post_id = $1")
+                .bind(post_id)
+                .fetch_all(&self.pool)
+                .await
+                .map_err(|e| Error::database_with_source("Failed to load post categories", e))?;
+        self.section_owners(&category_ids).await
+    }
+
+    /// Active members of any of the groups, each once
+    pub async fn recipients(&self, group_ids: &[Uuid]) -> Result<Vec<GroupRecipient>> {
+        sqlx::query_as(
+            "SELECT DISTINCT u.id AS user_id, u.email, u.display_name \
+             FROM user_group_members gm JOIN users u ON u.id = gm.user_id \
+             WHERE gm.group_id = ANY($1) AND u.status = 'active' AND u.deleted_at IS NULL",
+        )
+        .bind(group_ids)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load group members", e))
+    }
+
+    /// What a user gains from their groups
+    pub async fn grants_for(&self, user_id: Uuid) -> Result<Arc<GroupGrants>> {
+        if let Some((loaded_at, grants)) = self.grants.lock().get(&user_id) {
+            if loaded_at.elapsed() < GRANTS_CACHE_TTL {
+                return Ok(grants.clone());
+            }
+        }
+
+        let rows: Vec<MembershipRow> = sqlx::query_as(
+            "SELECT g.id AS group_id, gm.is_manager, g.roles, g.capabilities \
+             FROM user_group_members gm JOIN user_groups g ON g.id = gm.group_id \
+             WHERE gm.user_id = $1",
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load group grants", e))?;
+        let grants = Arc::new(combine_grants(rows));
+
+        let mut cache = self.grants.lock();
+        cache.retain(|_, (loaded_at, _)| loaded_at.elapsed() < GRANTS_CACHE_TTL);
+        cache.insert(user_id, (Instant::now(), grants.clone()));
+        Ok(grants)
+    }
+
+    fn forget_grants(&self) {
+        self.grants.lock().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_input() {
+        let permissions = PermissionChecker::with_default_roles();
+        let input = GroupInput {
+            name: "  Marketing Team ".to_string(),
+            description: Some("  ".to_string()),
+            roles: vec![
+                "editor".to_string(),
+                "author".to_string(),
+                "editor".to_string(),
+            ],
+            capabilities: vec!["posts:publish".to_string(), " media:* ".to_string()],
+            ..Default::default()
+        }
+        .normalize(&permissions)
+        .unwrap();
+        assert_eq!(input.name, "Marketing Team");
+        assert_eq!(input.slug.as_deref(), Some("marketing-team"));
+        assert_eq!(input.description, None);
+        assert_eq!(input.roles, ["author", "editor"]);
+        assert_eq!(input.capabilities, ["media:*", "posts:publish"]);
+
+        let unknown_role = GroupInput {
+            name: "Ops".to_string(),
+            roles: vec!["overlord".to_string()],
+            ..Default::default()
+        };
+        assert!(unknown_role.normalize(&permissions).is_err());
+
+        let bad_capability = GroupInput {
+            name: "Ops".to_string(),
+            capabilities: vec!["publish".to_string()],
+            ..Default::default()
+        };
+        assert!(bad_capability.normalize(&permissions).is_err());
+
+        let no_slug = GroupInput {
+            name: "!!!".to_string(),
+            ..Default::default()
+        };
+        assert!(no_slug.normalize(&permissions).is_err());
+    }
+
+    #[test]
+    fn test_grants() {
+        let marketing = Uuid::new_v4();
+        let design = Uuid::new_v4();
+        let grants = combine_grants(vec![
+            MembershipRow {
+                group_id: marketing,
+                is_manager: true,
+                roles: Json(vec!["editor".to_string()]),
+                capabilities: Json(vec!["posts:publish".to_string()]),
+            },
+            MembershipRow {
+                group_id: design,
+                is_manager: false,
+                roles: Json(vec!["editor".to_string(), "author".to_string()]),
+                capabilities: Json(vec!["media:*".to_string()]),
+            },
+        ]);
+
+        assert_eq!(grants.roles, ["author", "editor"]);
+        assert!(grants.manages(marketing));
+        assert!(!grants.manages(design));
+        assert!(grants.has_capability("media", "delete"));
+        assert!(grants.has_capability("posts", "publish"));
+        assert!(!grants.has_capability("posts", "delete"));
+
+        assert!(grants.may_change_section(&[]));
+        assert!(grants.may_change_section(&[Uuid::new_v4(), design]));
+        assert!(!grants.may_change_section(&[Uuid::new_v4()]));
+        assert!(!GroupGrants::default().may_change_section(&[marketing]));
+    }
+}
@@ -0,0 +1,1705 @@
+//! HTTP Message Signatures
+//!
+//! RFC 9421 signatures for server-to-server integrations, for security teams
+//! that do not accept a shared HMAC secret on its own:
+//!
+//! - trusted integrations register the public half of an Ed25519, ECDSA P-256
+//!   or RSA key under their `keyid`; requests carrying a `Signature-Input`
+//!   header are verified by the [`crate::middleware::http_signatures`] layer
+//!   and handlers read the result through [`VerifiedSignature`]. Signed
+//!   requests without a bearer token act as whoever registered the key
+//! - the site signs its own outbound requests, such as webhooks, with an
+//!   Ed25519 or P-256 key whose public half is published as a JWK set at
+//!   [`SIGNATURE_DIRECTORY_PATH`]
+//!
+//! Inbound signatures must cover the method and target URI, plus a
+//! `Content-Digest` (RFC 9530) of the body when there is one. They must carry
+//! `created`, `keyid` and `nonce` parameters, are accepted for
+//! [`MAX_SIGNATURE_AGE`] and every nonce is accepted once per key.
+//!
+//! Outbound private keys are stored sealed with AES-256-GCM under a key
+//! derived from the site secret, so rotating that secret retires them.
+
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use base64::engine::general_purpose::{STANDARD, URL_SAFE, URL_SAFE_NO_PAD};
+use base64::Engine as _;
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::SystemRandom;
+use ring::signature::{
+    self, EcdsaKeyPair, Ed25519KeyPair, KeyPair, UnparsedPublicKey, ECDSA_P256_SHA256_FIXED_SIGNING,
+};
+use rustpress_cache::Cache;
+use rustpress_core::error::{Error, Result};
+use serde::{Deserialize, Serialize, Serializer};
+use sha2::{Digest, Sha256, Sha512};
+use sqlx::{FromRow, PgPool};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// Where the public halves of the site's signing keys are published
+pub const SIGNATURE_DIRECTORY_PATH: &str = "/.well-known/http-message-signatures-directory";
+
+/// Content type of the signing key directory
+pub const SIGNATURE_DIRECTORY_CONTENT_TYPE: &str =
+    "application/http-message-signatures-directory+json";
+
+/// How long after its `created` time a signature is accepted
+pub const MAX_SIGNATURE_AGE: Duration = Duration::from_secs(300);
+
+/// How far in the future a `created` time may be, for clock drift
+const MAX_CLOCK_SKEW_SECS: i64 = 30;
+
+/// Largest body a signed request may carry
+pub const MAX_SIGNED_BODY_BYTES: usize = 10 * 1024 * 1024;
+
+/// Sent with rejections so integrations know what to sign
+pub const ACCEPT_SIGNATURE: &str =
+    r#"sig1=("@method" "@target-uri" "content-digest");created;keyid;nonce"#;
+
+/// Claim naming the key of a user authenticated by a signature
+pub const SIGNATURE_KEY_CLAIM: &str = "http_signature_key";
+
+/// Label of the signatures the site adds to outbound requests
+const OUTBOUND_LABEL: &str = "rustpress";
+
+/// Longest accepted key name
+const MAX_KEY_NAME_LENGTH: usize = 100;
+
+/// Longest accepted `keyid`
+const MAX_KEY_ID_LENGTH: usize = 255;
+
+/// How long an inbound key lookup, including a failed one, is reused
+const KEY_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// Cached inbound key lookups kept before expired ones are dropped
+const KEY_CACHE_CAPACITY: usize = 1024;
+
+/// Associated data of sealed private keys
+const SEALED_KEY_AAD: &[u8] = b"rustpress-http-signature-key";
+
+const KEY_COLUMNS: &str = "id, key_id, name, direction, algorithm, public_key, created_by, \
+     expires_at, last_used_at, revoked_at, created_at, updated_at";
+
+/// Whether a key verifies requests from an integration or signs the site's own
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyDirection {
+    Inbound,
+    Outbound,
+}
+
+impl KeyDirection {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Inbound => "inbound",
+            Self::Outbound => "outbound",
+        }
+    }
+}
+
+impl TryFrom<String> for KeyDirection {
+    type Error = String;
+
+    fn try_from(value: String) -> std::result::Result<Self, Self::Error> {
+        match value.as_str() {
+            "inbound" => Ok(Self::Inbound),
+            "outbound" => Ok(Self::Outbound),
+            _ => Err(format!("unknown key direction '{}'", value)),
+        }
+    }
+}
+
+/// Signature algorithms from the RFC 9421 registry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SignatureAlgorithm {
+    #[serde(rename = "ed25519")]
+    Ed25519,
+    #[serde(rename = "ecdsa-p256-sha256")]
+    EcdsaP256Sha256,
+    #[serde(rename = "rsa-pss-sha512")]
+    RsaPssSha512,
+    #[serde(rename = "rsa-v1_5-sha256")]
+    RsaV15Sha256,
+}
+
+impl SignatureAlgorithm {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Ed25519 => "ed25519",
+            Self::EcdsaP256Sha256 => "ecdsa-p256-sha256",
+            Self::RsaPssSha512 => "rsa-pss-sha512",
+            Self::RsaV15Sha256 => "rsa-v1_5-sha256",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "ed25519" => Some(Self::Ed25519),
+            "ecdsa-p256-sha256" => Some(Self::EcdsaP256Sha256),
+            "rsa-pss-sha512" => Some(Self::RsaPssSha512),
+            "rsa-v1_5-sha256" => Some(Self::RsaV15Sha256),
+            _ => None,
+        }
+    }
+
+    /// Whether the site can generate keys of this algorithm to sign with
+    pub fn can_sign(self) -> bool {
+        matches!(self, Self::Ed25519 | Self::EcdsaP256Sha256)
+    }
+
+    /// Check `signature` over `message`. Ed25519 keys are the raw 32 bytes,
+    /// P-256 keys an uncompressed point and RSA keys a PKCS#1 `RSAPublicKey`.
+    pub fn verify(self, public_key: &[u8], message: &[u8], signature: &[u8]) -> bool {
+        let algorithm: &dyn signature::VerificationAlgorithm = match self {
+            Self::Ed25519 => &signature::ED25519,
+            Self::EcdsaP256Sha256 => &signature::ECDSA_P256_SHA256_FIXED,
+            Self::RsaPssSha512 => &signature::RSA_PSS_2048_8192_SHA512,
+            Self::RsaV15Sha256 => &signature::RSA_PKCS1_2048_8192_SHA256,
+        };
+        UnparsedPublicKey::new(algorithm, public_key)
+            .verify(message, signature)
+            .is_ok()
+    }
+}
+
+impl TryFrom<String> for SignatureAlgorithm {
+    type Error = String;
+
+    fn try_from(value: String) -> std::result::Result<Self, Self::Error> {
+        Self::parse(&value).ok_or_else(|| format!("unknown signature algorithm '{}'", value))
+    }
+}
+
+/// A registered signature key; private halves never leave the service
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct HttpSignatureKey {
+    pub id: Uuid,
+    /// The `keyid` signatures name
+    pub key_id: String,
+    pub name: String,
+    #[sqlx(try_from = "String")]
+    pub direction: KeyDirection,
+    #[sqlx(try_from = "String")]
+    pub algorithm: SignatureAlgorithm,
+    #[serde(serialize_with = "serialize_base64")]
+    pub public_key: Vec<u8>,
+    pub created_by: Option<Uuid>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl HttpSignatureKey {
+    /// Whether the key is neither revoked nor expired
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.revoked_at.is_none() && self.expires_at.is_none_or(|at| at > now)
+    }
+
+    /// The public key as a JWK, for keys the site signs with
+    pub fn jwk(&self) -> Option<serde_json::Value> {
+        match self.algorithm {
+            SignatureAlgorithm::Ed25519 => Some(serde_json::json!({
+                "kty": "OKP",
+                "crv": "Ed25519",
+                "kid": self.key_id,
+                "x": URL_SAFE_NO_PAD.encode(&self.public_key),
+            })),
+            SignatureAlgorithm::EcdsaP256Sha256 if self.public_key.len() == 65 => {
+                Some(serde_json::json!({
+                    "kty": "EC",
+                    "crv": "P-256",
+                    "kid": self.key_id,
+                    "x": URL_SAFE_NO_PAD.encode(&self.public_key[1..33]),
+                    "y": URL_SAFE_NO_PAD.encode(&self.public_key[33..]),
+                }))
+            }
+            _ => None,
+        }
+    }
+}
+
+fn serialize_base64<S: Serializer>(
+    bytes: &[u8],
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serializer.serialize_str(&STANDARD.encode(bytes))
+}
+
+/// Public key of a trusted integration
+#[derive(Debug, Clone, Deserialize)]
+pub struct InboundKeyInput {
+    pub name: String,
+    /// The `keyid` the integration signs with
+    pub key_id: String,
+    pub algorithm: SignatureAlgorithm,
+    /// PEM (`PUBLIC KEY` or `RSA PUBLIC KEY`) or base64 of the raw key
+    pub public_key: String,
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// Signing key for the site to generate
+#[derive(Debug, Clone, Deserialize)]
+pub struct OutboundKeyInput {
+    pub name: String,
+    #[serde(default = "default_outbound_algorithm")]
+    pub algorithm: SignatureAlgorithm,
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+fn default_outbound_algorithm() -> SignatureAlgorithm {
+    SignatureAlgorithm::Ed25519
+}
+
+/// Why an inbound signature was rejected
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SignatureError {
+    #[error("Malformed {0} header")]
+    Malformed(&'static str),
+    #[error("Signature is missing the {0} parameter")]
+    MissingParameter(&'static str),
+    #[error("Signature must cover {0}")]
+    MissingComponent(&'static str),
+    #[error("Signature covers unsupported component {0}")]
+    UnsupportedComponent(String),
+    #[error("Signature covers {0}, which the request does not have")]
+    AbsentComponent(String),
+    #[error("Signature was created too long ago or in the future")]
+    Stale,
+    #[error("Signature has expired")]
+    Expired,
+    #[error("Unknown or inactive signature key")]
+    UnknownKey,
+    #[error("Signature algorithm does not match the key")]
+    AlgorithmMismatch,
+    #[error("Content-Digest does not match the body")]
+    DigestMismatch,
+    #[error("Signature verification failed")]
+    Invalid,
+    #[error("Signature nonce has already been used")]
+    Replayed,
+    #[error("Signatures cannot be verified right now")]
+    Unavailable,
+}
+
+/// A verified inbound signature, added to the request extensions
+#[derive(Debug, Clone, Serialize)]
+pub struct VerifiedSignature {
+    /// Row id of the integration's key
+    pub key: Uuid,
+    pub key_id: String,
+    /// Name the integration was registered under
+    pub name: String,
+    pub label: String,
+    pub components: Vec<String>,
+    pub created: DateTime<Utc>,
+    /// User the integration acts as: whoever registered its key
+    pub user_id: Option<Uuid>,
+}
+
+/// Active user a signing key acts for
+#[derive(Debug, Clone, FromRow)]
+pub struct SignatureKeyOwner {
+    pub user_id: Uuid,
+    pub email: String,
+    pub role: String,
+}
+
+// =============================================================================
+// Structured fields (RFC 8941), as far as signatures use them
+// =============================================================================
+
+/// A parameter value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BareItem {
+    Integer(i64),
+    String(String),
+    Token(String),
+    Boolean(bool),
+}
+
+impl BareItem {
+    fn serialize_into(&self, out: &mut String) {
+        match self {
+            Self::Integer(n) => {
+                let _ = write!(out, "{}", n);
+            }
+            Self::String(s) => {
+                out.push('"');
+                for c in s.chars() {
+                    if c == '"' || c == '\\' {
+                        out.push('\\');
+                    }
+                    out.push(c);
+                }
+                out.push('"');
+            }
+            Self::Token(t) => out.push_str(t),
+            Self::Boolean(true) => {}
+            Self::Boolean(false) => out.push_str("=?0"),
+        }
+    }
+}
+
+/// Covered components and parameters of one signature, in header order
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SignatureParams {
+    pub components: Vec<String>,
+    pub params: Vec<(String, BareItem)>,
+}
+
+impl SignatureParams {
+    fn param(&self, name: &str) -> Option<&BareItem> {
+        self.params
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, v)| v)
+    }
+
+    fn integer(&self, name: &str) -> Option<i64> {
+        match self.param(name)? {
+            BareItem::Integer(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    fn string(&self, name: &str) -> Option<&str> {
+        match self.param(name)? {
+            BareItem::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn created(&self) -> Option<i64> {
+        self.integer("created")
+    }
+
+    pub fn expires(&self) -> Option<i64> {
+        self.integer("expires")
+    }
+
+    pub fn keyid(&self) -> Option<&str> {
+        self.string("keyid")
+    }
+
+    pub fn nonce(&self) -> Option<&str> {
+        self.string("nonce")
+    }
+
+    pub fn alg(&self) -> Option<&str> {
+        self.string("alg")
+    }
+
+    pub fn covers(&self, component: &str) -> bool {
+        self.components.iter().any(|c| c == component)
+    }
+
+    /// The `@signature-params` value
+    pub fn serialize(&self) -> String {
+        let mut out = String::from("(");
+        for (i, component) in self.components.iter().enumerate() {
+            if i > 0 {
+                out.push(' ');
+            }
+            BareItem::String(component.clone()).serialize_into(&mut out);
+        }
+        out.push(')');
+        for (key, value) in &self.params {
+            out.push(';');
+            out.push_str(key);
+            if !matches!(value, BareItem::Boolean(_)) {
+                out.push('=');
+            }
+            value.serialize_into(&mut out);
+        }
+        out
+    }
+}
+
+struct FieldParser<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> FieldParser<'a> {
+    fn new(input: &'a str) -> Self {
+        let mut parser = Self {
+            input: input.as_bytes(),
+            pos: 0,
+        };
+        parser.skip_ows();
+        parser
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, c: u8) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos >= self.input.len()
+    }
+
+    fn skip_sp(&mut self) {
+        while self.peek() == Some(b' ') {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_ows(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t')) {
+            self.pos += 1;
+        }
+    }
+
+    fn take_while(&mut self, pred: impl Fn(u8) -> bool) -> &'a str {
+        let start = self.pos;
+        while self.peek().is_some_and(&pred) {
+            self.pos += 1;
+        }
+        // Only ASCII bytes are ever accepted by the predicates
+        std::str::from_utf8(&self.input[start..self.pos]).unwrap_or_default()
+    }
+
+    fn key(&mut self) -> Option<String> {
+        if !self
+            .peek()
+            .is_some_and(|c| c.is_ascii_lowercase() || c == b'*')
+        {
+            return None;
+        }
+        let key = self.take_while(|c| {
+            c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, b'_' | b'-' | b'.' | b'*')
+        });
+        Some(key.to_string())
+    }
+
+    fn string(&mut self) -> Option<String> {
+        if !self.eat(b'"') {
+            return None;
+        }
+        let mut out = String::new();
+        loop {
+            let c = self.peek()?;
+            self.pos += 1;
+            match c {
+                b'"' => return Some(out),
+                b'\\' => {
+                    let escaped = self.peek().filter(|c| matches!(c, b'"' | b'\\'))?;
+                    self.pos += 1;
+                    out.push(escaped as char);
+                }
+                0x20..=0x7e => out.push(c as char),
+                _ => return None,
+            }
+        }
+    }
+
+    fn integer(&mut self) -> Option<i64> {
+        let negative = self.eat(b'-');
+        let digits = self.take_while(|c| c.is_ascii_digit());
+        if digits.is_empty() || digits.len() > 15 {
+            return None;
+        }
+        let n: i64 = digits.parse().ok()?;
+        Some(if negative { -n } else { n })
+    }
+
+    fn byte_sequence(&mut self) -> Option<Vec<u8>> {
+        if !self.eat(b':') {
+            return None;
+        }
+        let encoded =
+            self.take_while(|c| c.is_ascii_alphanumeric() || matches!(c, b'+' | b'/' | b'='));
+        if !self.eat(b':') {
+            return None;
+        }
+        STANDARD.decode(encoded).ok()
+    }
+
+    fn bare_item(&mut self) -> Option<BareItem> {
+        match self.peek()? {
+            b'"' => self.string().map(BareItem::String),
+            b'-' | b'0'..=b'9' => self.integer().map(BareItem::Integer),
+            b'?' => {
+                self.pos += 1;
+                let value = match self.peek()? {
+                    b'0' => false,
+                    b'1' => true,
+                    _ => return None,
+                };
+                self.pos += 1;
+                Some(BareItem::Boolean(value))
+            }
+            c if c.is_ascii_alphabetic() || c == b'*' => {
+                let token = self
+                    .take_while(|c| c.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~:/".contains(&c));
+                Some(BareItem::Token(token.to_string()))
+            }
+            _ => None,
+        }
+    }
+
+    fn parameters(&mut self) -> Option<Vec<(String, BareItem)>> {
+        let mut params: Vec<(String, BareItem)> = Vec::new();
+        while self.eat(b';') {
+            self.skip_sp();
+            let key = self.key()?;
+            let value = if self.eat(b'=') {
+                self.bare_item()?
+            } else {
+                BareItem::Boolean(true)
+            };
+            params.retain(|(k, _)| *k != key);
+            params.push((key, value));
+        }
+        Some(params)
+    }
+
+    /// Move past the comma between dictionary members; `false` at the end
+    fn next_member(&mut self) -> Option<bool> {
+        self.skip_ows();
+        if self.at_end() {
+            return Some(false);
+        }
+        if !self.eat(b',') {
+            return None;
+        }
+        self.skip_ows();
+        (!self.at_end()).then_some(true)
+    }
+}
+
+/// Parse a `Signature-Input` dictionary
+pub fn parse_signature_input(value: &str) -> Option<Vec<(String, SignatureParams)>> {
+    let mut parser = FieldParser::new(value);
+    let mut members = Vec::new();
+    if parser.at_end() {
+        return Some(members);
+    }
+    loop {
+        let label = parser.key()?;
+        if !parser.eat(b'=') || !parser.eat(b'(') {
+            return None;
+        }
+        let mut components = Vec::new();
+        loop {
+            parser.skip_sp();
+            if parser.eat(b')') {
+                break;
+            }
+            components.push(parser.string()?);
+            // Component parameters (`;sf`, `;key`, `;req`, ...) are not supported
+            if parser.peek() == Some(b';') {
+                return None;
+            }
+            if !matches!(parser.peek(), Some(b' ' | b')')) {
+                return None;
+            }
+        }
+        let params = parser.parameters()?;
+        members.retain(|(l, _): &(String, SignatureParams)| *l != label);
+        members.push((label, SignatureParams { components, params }));
+        if !parser.next_member()? {
+            return Some(members);
+        }
+    }
+}
+
+/// Parse a dictionary of byte sequences, such as `Signature` or
+/// `Content-Digest`
+pub fn parse_byte_dictionary(value: &str) -> Option<Vec<(String, Vec<u8>)>> {
+    let mut parser = FieldParser::new(value);
+    let mut members = Vec::new();
+    if parser.at_end() {
+        return Some(members);
+    }
+    loop {
+        let key = parser.key()?;
+        if !parser.eat(b'=') {
+            return None;
+        }
+        let bytes = parser.byte_sequence()?;
+        parser.parameters()?;
+        members.retain(|(k, _): &(String, Vec<u8>)| *k != key);
+        members.push((key, bytes));
+        if !parser.next_member()? {
+            return Some(members);
+        }
+    }
+}
+
+// =============================================================================
+// Signature bases
+// =============================================================================
+
+/// The parts of a request a signature base is built from
+#[derive(Debug, Clone, Default)]
+pub struct SignatureTarget {
+    pub method: String,
+    pub scheme: String,
+    pub authority: String,
+    pub path: String,
+    pub query: Option<String>,
+    /// Field values by lowercase name, repeated fields joined with `, `
+    fields: HashMap<String, String>,
+}
+
+impl SignatureTarget {
+    pub fn new(
+        method: impl Into<String>,
+        scheme: impl Into<String>,
+        authority: impl Into<String>,
+        path: impl Into<String>,
+        query: Option<String>,
+    ) -> Self {
+        Self {
+            method: method.into(),
+            scheme: scheme.into().to_ascii_lowercase(),
+            authority: authority.into().to_ascii_lowercase(),
+            path: path.into(),
+            query,
+            fields: HashMap::new(),
+        }
+    }
+
+    /// Add header fields; values that are not valid text are skipped
+    pub fn with_fields<'a>(
+        mut self,
+        fields: impl IntoIterator<Item = (&'a str, &'a [u8])>,
+    ) -> Self {
+        for (name, value) in fields {
+            let Ok(value) = std::str::from_utf8(value) else {
+                continue;
+            };
+            let value = value.trim();
+            self.fields
+                .entry(name.to_ascii_lowercase())
+                .and_modify(|existing| {
+                    existing.push_str(", ");
+                    existing.push_str(value);
+                })
+                .or_insert_with(|| value.to_string());
+        }
+        self
+    }
+
+    pub fn field(&self, name: &str) -> Option<&str> {
+        self.fields.get(name).map(String::as_str)
+    }
+
+    fn request_target(&self) -> String {
+        let path = if self.path.is_empty() {
+            "/"
+        } else {
+            &self.path
+        };
+        match &self.query {
+            Some(query) => format!("{}?{}", path, query),
+            None => path.to_string(),
+        }
+    }
+
+    /// Value of a covered component
+    fn component(&self, name: &str) -> std::result::Result<String, SignatureError> {
+        let value = match name {
+            "@method" => self.method.clone(),
+            "@target-uri" => format!(
+                "{}://{}{}",
+                self.scheme,
+                self.authority,
+                self.request_target()
+            ),
+            "@authority" => self.authority.clone(),
+            "@scheme" => self.scheme.clone(),
+            "@request-target" => self.request_target(),
+            "@path" => {
+                if self.path.is_empty() {
+                    "/".to_string()
+                } else {
+                    self.path.clone()
+                }
+            }
+            "@query" => format!("?{}", self.query.as_deref().unwrap_or_default()),
+            _ if name.starts_with('@') => {
+                return Err(SignatureError::UnsupportedComponent(name.to_string()))
+            }
+            _ => {
+                if name.bytes().any(|c| c.is_ascii_uppercase()) {
+                    return Err(SignatureError::UnsupportedComponent(name.to_string()));
+                }
+                self.field(name)
+                    .ok_or_else(|| SignatureError::AbsentComponent(name.to_string()))?
+                    .to_string()
+            }
+        };
+        Ok(value)
+    }
+
+    /// The signature base of `params` over this request
+    pub fn signature_base(
+        &self,
+        params: &SignatureParams,
+    ) -> std::result::Result<String, SignatureError> {
+        let mut base = String::new();
+        for (i, name) in params.components.iter().enumerate() {
+            if params.components[..i].contains(name) {
+                return Err(SignatureError::Malformed("Signature-Input"));
+            }
+            let value = self.component(name)?;
+            let _ = writeln!(base, "\"{}\": {}", name, value);
+        }
+        let _ = write!(base, "\"@signature-params\": {}", params.serialize());
+        Ok(base)
+    }
+}
+
+/// `Content-Digest` value of a body
+pub fn content_digest(body: &[u8]) -> String {
+    format!("sha-256=:{}:", STANDARD.encode(Sha256::digest(body)))
+}
+
+/// Check a `Content-Digest` value against the body; at least one digest must
+/// use a supported algorithm and every supported one must match
+pub fn digest_matches(header: &str, body: &[u8]) -> bool {
+    let Some(digests) = parse_byte_dictionary(header) else {
+        return false;
+    };
+    let mut checked = false;
+    for (algorithm, digest) in digests {
+        let matches = match algorithm.as_str() {
+            "sha-256" => Sha256::digest(body).as_slice() == digest.as_slice(),
+            "sha-512" => Sha512::digest(body).as_slice() == digest.as_slice(),
+            _ => continue,
+        };
+        if !matches {
+            return false;
+        }
+        checked = true;
+    }
+    checked
+}
+
+/// Check what an inbound signature covers and its time window
+pub fn check_signature_params(
+    params: &SignatureParams,
+    has_body: bool,
+    now: i64,
+) -> std::result::Result<(), SignatureError> {
+    if params.keyid().is_none() {
+        return Err(SignatureError::MissingParameter("keyid"));
+    }
+    if params.nonce().is_none_or(str::is_empty) {
+        return Err(SignatureError::MissingParameter("nonce"));
+    }
+    let created = params
+        .created()
+        .ok_or(SignatureError::MissingParameter("created"))?;
+    if created > now + MAX_CLOCK_SKEW_SECS || now - created > MAX_SIGNATURE_AGE.as_secs() as i64 {
+        return Err(SignatureError::Stale);
+    }
+    if params.expires().is_some_and(|expires| expires <= now) {
+        return Err(SignatureError::Expired);
+    }
+
+    if !params.covers("@method") {
+        return Err(SignatureError::MissingComponent("@method"));
+    }
+    let covers_target = params.covers("@target-uri")
+        || (params.covers("@authority")
+            && (params.covers("@request-target")
+                || (params.covers("@path") && params.covers("@query"))));
+    if !covers_target {
+        return Err(SignatureError::MissingComponent(
+            "@target-uri, or @authority with @request-target",
+        ));
+    }
+    if has_body && !params.covers("content-digest") {
+        return Err(SignatureError::MissingComponent("content-digest"));
+    }
+    Ok(())
+}
+
+// =============================================================================
+// Keys
+// =============================================================================
+
+/// Import a public key given as PEM or base64
+pub fn decode_public_key(algorithm: SignatureAlgorithm, input: &str) -> Result<Vec<u8>> {
+    let input = input.trim();
+    let invalid = |message: &str| Error::invalid_input("public_key", message);
+
+    let key = if let Some(pem) = input.strip_prefix("-----BEGIN ") {
+        let (label, rest) = pem
+            .split_once("-----")
+            .ok_or_else(|| invalid("Malformed PEM"))?;
+        let body = rest
+            .split("-----END ")
+            .next()
+            .unwrap_or_default()
+            .split_whitespace()
+            .collect::<String>();
+        let der = STANDARD
+            .decode(body)
+            .map_err(|_| invalid("Malformed PEM"))?;
+        match label {
+            "PUBLIC KEY" => spki_public_key(&der)
+                .ok_or_else(|| invalid("Malformed SubjectPublicKeyInfo"))?
+                .to_vec(),
+            "RSA PUBLIC KEY" => der,
+            _ => return Err(invalid("Expected a PUBLIC KEY or RSA PUBLIC KEY PEM block")),
+        }
+    } else {
+        let compact: String = input.split_whitespace().collect();
+        STANDARD
+            .decode(&compact)
+            .or_else(|_| URL_SAFE.decode(&compact))
+            .or_else(|_| URL_SAFE_NO_PAD.decode(&compact))
+            .map_err(|_| invalid("Expected PEM or base64"))?
+    };
+
+    let valid = match algorithm {
+        SignatureAlgorithm::Ed25519 => key.len() == 32,
+        SignatureAlgorithm::EcdsaP256Sha256 => key.len() == 65 && key[0] == 0x04,
+        SignatureAlgorithm::RsaPssSha512 | SignatureAlgorithm::RsaV15Sha256 => {
+            der_element(&key, 0x30).is_some()
+        }
+    };
+    if !valid {
+        return Err(invalid("Key does not match the algorithm"));
+    }
+    Ok(key)
+}
+
+/// Contents of the DER element with `tag` at the start of `input`, and the
+/// bytes after it
+fn der_element(input: &[u8], tag: u8) -> Option<(&[u8], &[u8])> {
+    let (&found, rest) = input.split_first()?;
+    if found != tag {
+        return None;
+    }
+    let (&len, rest) = rest.split_first()?;
+    let (len, rest) = if len < 0x80 {
+        (len as usize, rest)
+    } else {
+        let octets = (len & 0x7f) as usize;
+        if octets == 0 || octets > 4 || rest.len() < octets {
+            return None;
+        }
+        let (len, rest) = rest.split_at(octets);
+        (len.iter().fold(0usize, |n, &b| (n << 8) | b as usize), rest)
+    };
+    (rest.len() >= len).then(|| rest.split_at(len))
+}
+
+/// The key bits of a SubjectPublicKeyInfo
+fn spki_public_key(der: &[u8]) -> Option<&[u8]> {
+    let (spki, _) = der_element(der, 0x30)?;
+    let (_, rest) = der_element(spki, 0x30)?;
+    let (bits, _) = der_element(rest, 0x03)?;
+    let (&unused_bits, key) = bits.split_first()?;
+    (unused_bits == 0).then_some(key)
+}
+
+/// The key bits of the SubjectPublicKeyInfo of a DER X.509 certificate
+pub(crate) fn certificate_public_key(der: &[u8]) -> Option<&[u8]> {
+    let (certificate, _) = der_element(der, 0x30)?;
+    let (tbs, _) = der_element(certificate, 0x30)?;
+    // [0] version is optional
+    let mut rest = match tbs.first() {
+        Some(0xa0) => der_element(tbs, 0xa0)?.1,
+        _ => tbs,
+    };
+    // serialNumber, signature, issuer, validity, subject
+    for _ in 0..5 {
+        let tag = *rest.first()?;
+        rest = der_element(rest, tag)?.1;
+    }
+    spki_public_key(rest)
+}
+
+/// A private key the site signs with
+enum SigningKey {
+    Ed25519(Ed25519KeyPair),
+    EcdsaP256(EcdsaKeyPair),
+}
+
+impl SigningKey {
+    /// Generate a key, returning it as PKCS#8 with its public half
+    fn generate(algorithm: SignatureAlgorithm) -> Result<(Vec<u8>, Vec<u8>)> {
+        let rng = SystemRandom::new();
+        let failed = |_| Error::internal("Failed to generate signing key");
+        let pkcs8 = match algorithm {
+            SignatureAlgorithm::Ed25519 => Ed25519KeyPair::generate_pkcs8(&rng).map_err(failed)?,
+            SignatureAlgorithm::EcdsaP256Sha256 => {
+                EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &rng)
+                    .map_err(failed)?
+            }
+            _ => {
+                return Err(Error::invalid_input(
+                    "algorithm",
+                    "Outbound keys must be ed25519 or ecdsa-p256-sha256",
+                ))
+            }
+        };
+        let key = Self::from_pkcs8(algorithm, pkcs8.as_ref())?;
+        Ok((pkcs8.as_ref().to_vec(), key.public_key().to_vec()))
+    }
+
+    fn from_pkcs8(algorithm: SignatureAlgorithm, pkcs8: &[u8]) -> Result<Self> {
+        let rejected = |_| Error::internal("Stored signing key is not valid");
+        match algorithm {
+            SignatureAlgorithm::Ed25519 => Ed25519KeyPair::from_pkcs8(pkcs8)
+                .map(Self::Ed25519)
+                .map_err(rejected),
+            SignatureAlgorithm::EcdsaP256Sha256 => EcdsaKeyPair::from_pkcs8(
+                &ECDSA_P256_SHA256_FIXED_SIGNING,
+                pkcs8,
+                &SystemRandom::new(),
+            )
+            .map(Self::EcdsaP256)
+            .map_err(rejected),
+            _ => Err(Error::internal("Unsupported signing algorithm")),
+        }
+    }
+
+    fn public_key(&self) -> &[u8] {
+        match self {
+            Self::Ed25519(pair) => pair.public_key().as_ref(),
+            Self::EcdsaP256(pair) => pair.public_key().as_ref(),
+        }
+    }
+
+    fn sign(&self, message: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Self::Ed25519(pair) => Ok(pair.sign(message).as_ref().to_vec()),
+            Self::EcdsaP256(pair) => pair
+                .sign(&SystemRandom::new(), message)
+                .map(|sig| sig.as_ref().to_vec())
+                .map_err(|_| Error::internal("Failed to sign request")),
+        }
+    }
+}
+
+/// Seals private keys at rest
+struct KeySealer {
+    key: LessSafeKey,
+}
+
+impl KeySealer {
+    fn new(secret: &str) -> Self {
+        let key = Sha256::digest(format!("http-signatures:{}", secret));
+        let key = UnboundKey::new(&AES_256_GCM, &key).expect("SHA-256 output is an AES-256 key");
+        Self {
+            key: LessSafeKey::new(key),
+        }
+    }
+
+    fn seal(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let mut nonce = [0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut nonce);
+        let mut sealed = plaintext.to_vec();
+        self.key
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(SEALED_KEY_AAD),
+                &mut sealed,
+            )
+            .map_err(|_| Error::internal("Failed to seal signing key"))?;
+        let mut out = nonce.to_vec();
+        out.extend_from_slice(&sealed);
+        Ok(out)
+    }
+
+    fn open(&self, sealed: &[u8]) -> Option<Vec<u8>> {
+        if sealed.len() < NONCE_LEN {
+            return None;
+        }
+        let (nonce, sealed) = sealed.split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce).ok()?;
+        let mut buffer = sealed.to_vec();
+        let plaintext = self
+            .key
+            .open_in_place(nonce, Aad::from(SEALED_KEY_AAD), &mut buffer)
+            .ok()?;
+        Some(plaintext.to_vec())
+    }
+}
+
+/// Key lookup cached at an instant; `None` remembers unknown key ids
+type CachedKeyLookup = (Instant, Option<Arc<HttpSignatureKey>>);
+
+/// Signature keys, inbound verification and outbound signing
+pub struct HttpSignatureService {
+    pool: PgPool,
+    /// Shared cache recording used nonces, so a signature is only accepted
+    /// once across all instances
+    cache: Arc<Cache>,
+    sealer: KeySealer,
+    /// Inbound key lookups by `keyid`
+    inbound: Mutex<HashMap<String, CachedKeyLookup>>,
+}
+
+impl HttpSignatureService {
+    /// Create the service; outbound keys are sealed with a key derived from
+    /// `secret`
+    pub fn new(pool: PgPool, cache: Arc<Cache>, secret: &str) -> Self {
+        Self {
+            pool,
+            cache,
+            sealer: KeySealer::new(secret),
+            inbound: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Keys, newest first
+    pub async fn list_keys(
+        &self,
+        direction: Option<KeyDirection>,
+    ) -> Result<Vec<HttpSignatureKey>> {
+        sqlx::query_as(&format!(
+            r#"
+            SELECT {} FROM http_signature_keys
+            WHERE ($1::text IS NULL OR direction = $1)
+            ORDER BY created_at DESC
+            "#,
+            KEY_COLUMNS
+        ))
+        .bind(direction.map(KeyDirection::as_str))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to list signature keys", e))
+    }
+
+    pub async fn get_key(&self, id: Uuid) -> Result<HttpSignatureKey> {
+        sqlx::query_as(&format!(
+            "SELECT {} FROM http_signature_keys WHERE id = $1",
+            KEY_COLUMNS
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load signature key", e))?
+        .ok_or_else(|| Error::not_found("Signature key", id.to_string()))
+    }
+
+    /// Trust an integration's public key
+    pub async fn register_inbound_key(
+        &self,
+        created_by: Uuid,
+        input: InboundKeyInput,
+    ) -> Result<HttpSignatureKey> {
+        let name = validate_name(&input.name)?;
+        let key_id = input.key_id.trim();
+        if key_id.is_empty()
+            || key_id.len() > MAX_KEY_ID_LENGTH
+            || !key_id.bytes().all(|c| (0x20..=0x7e).contains(&c))
+        {
+            return Err(Error::invalid_input(
+                "key_id",
+                format!(
+                    "Key id must be 1 to {} printable ASCII characters",
+                    MAX_KEY_ID_LENGTH
+                ),
+            ));
+        }
+        let public_key = decode_public_key(input.algorithm, &input.public_key)?;
+
+        let key = self
+            .insert_key(
+                Uuid::now_v7(),
+                key_id,
+                name,
+                KeyDirection::Inbound,
+                input.algorithm,
+                &public_key,
+                None,
+                created_by,
+                input.expires_at,
+            )
+            .await?;
+        self.inbound.lock().remove(key_id);
+        Ok(key)
+    }
+
+    /// Generate a key for the site to sign outbound requests with
+    pub async fn create_outbound_key(
+        &self,
+        created_by: Uuid,
+        input: OutboundKeyInput,
+    ) -> Result<HttpSignatureKey> {
+        let name = validate_name(&input.name)?;
+        if !input.algorithm.can_sign() {
+            return Err(Error::invalid_input(
+                "algorithm",
+                "Outbound keys must be ed25519 or ecdsa-p256-sha256",
+            ));
+        }
+        let (pkcs8, public_key) = SigningKey::generate(input.algorithm)?;
+        let sealed = self.sealer.seal(&pkcs8)?;
+
+        let id = Uuid::now_v7();
+        self.insert_key(
+            id,
+            &format!("rustpress-{}", id.simple()),
+            name,
+            KeyDirection::Outbound,
+            input.algorithm,
+            &public_key,
+            Some(&sealed),
+            created_by,
+            input.expires_at,
+        )
+        .await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn insert_key(
+        &self,
+        id: Uuid,
+        key_id: &str,
+        name: &str,
+        direction: KeyDirection,
+        algorithm: SignatureAlgorithm,
+        public_key: &[u8],
+        private_key: Option<&[u8]>,
+        created_by: Uuid,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<HttpSignatureKey> {
+        let key: Option<HttpSignatureKey> = sqlx::query_as(&format!(
+            r#"
+            INSERT INTO http_signature_keys
+                (id, key_id, name, direction, algorithm, public_key, private_key, created_by,
+                 expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            ON CONFLICT (key_id) DO NOTHING
+            RETURNING {}
+            "#,
+            KEY_COLUMNS
+        ))
+        .bind(id)
+        .bind(key_id)
+        .bind(name)
+        .bind(direction.as_str())
+        .bind(algorithm.as_str())
+        .bind(public_key)
+        .bind(private_key)
+        .bind(created_by)
+        .bind(expires_at)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to create signature key", e))?;
+
+        key.ok_or_else(|| Error::invalid_input("key_id", "A key with this id already exists"))
+    }
+
+    /// Revoke a key; signatures made with it are rejected from now on
+    pub async fn revoke_key(&self, id: Uuid) -> Result<HttpSignatureKey> {
+        let key: HttpSignatureKey = sqlx::query_as(&format!(
+            r#"
+            UPDATE http_signature_keys
+            SET revoked_at = COALESCE(revoked_at, NOW()), updated_at = NOW()
+            WHERE id = $1
+            RETURNING {}
+            "#,
+            KEY_COLUMNS
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to revoke signature key", e))?
+        .ok_or_else(|| Error::not_found("Signature key", id.to_string()))?;
+
+        self.inbound.lock().remove(&key.key_id);
+        Ok(key)
+    }
+
+    /// The trusted integration key named by `key_id`
+    async fn inbound_key(&self, key_id: &str) -> Result<Option<Arc<HttpSignatureKey>>> {
+        if let Some((fetched_at, key)) = self.inbound.lock().get(key_id) {
+            if fetched_at.elapsed() < KEY_CACHE_TTL {
+                return Ok(key.clone());
+            }
+        }
+
+        let key: Option<HttpSignatureKey> = sqlx::query_as(&format!(
+            r#"
+            SELECT {} FROM http_signature_keys
+            WHERE key_id = $1 AND direction = 'inbound' AND revoked_at IS NULL
+            "#,
+            KEY_COLUMNS
+        ))
+        .bind(key_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to look up signature key", e))?;
+        let key = key.map(Arc::new);
+
+        let mut inbound = self.inbound.lock();
+        if inbound.len() >= KEY_CACHE_CAPACITY {
+            inbound.retain(|_, (fetched_at, _)| fetched_at.elapsed() < KEY_CACHE_TTL);
+            if inbound.len() >= KEY_CACHE_CAPACITY {
+                inbound.clear();
+            }
+        }
+        inbound.insert(key_id.to_string(), (Instant::now(), key.clone()));
+        Ok(key)
+    }
+
+    /// Verify the signature of an inbound request. The first signature in
+    /// `Signature-Input` is checked; requests signed by several parties are
+    /// accepted on the strength of that one.
+    pub async fn verify(
+        &self,
+        target: &SignatureTarget,
+        body: &[u8],
+    ) -> std::result::Result<VerifiedSignature, SignatureError> {
+        let inputs = target
+            .field("signature-input")
+            .and_then(parse_signature_input)
+            .ok_or(SignatureError::Malformed("Signature-Input"))?;
+        let signatures = target
+            .field("signature")
+            .and_then(parse_byte_dictionary)
+            .ok_or(SignatureError::Malformed("Signature"))?;
+        let (label, params) = inputs
+            .into_iter()
+            .next()
+            .ok_or(SignatureError::Malformed("Signature-Input"))?;
+        let signature = signatures
+            .iter()
+            .find(|(l, _)| *l == label)
+            .map(|(_, sig)| sig)
+            .ok_or(SignatureError::Malformed("Signature"))?;
+
+        let now = Utc::now();
+        check_signature_params(&params, !body.is_empty(), now.timestamp())?;
+        let key_id = params.keyid().unwrap_or_default();
+
+        let key = self
+            .inbound_key(key_id)
+            .await
+            .map_err(|e| {
+                tracing::warn!(error = %e, "Failed to look up HTTP signature key");
+                SignatureError::Unavailable
+            })?
+            .filter(|key| key.is_active(now))
+            .ok_or(SignatureError::UnknownKey)?;
+        if params
+            .alg()
+            .is_some_and(|alg| alg != key.algorithm.as_str())
+        {
+            return Err(SignatureError::AlgorithmMismatch);
+        }
+
+        if params.covers("content-digest") {
+            let header = target
+                .field("content-digest")
+                .ok_or_else(|| SignatureError::AbsentComponent("content-digest".to_string()))?;
+            if !digest_matches(header, body) {
+                return Err(SignatureError::DigestMismatch);
+            }
+        }
+
+        let base = target.signature_base(&params)?;
+        if !key
+            .algorithm
+            .verify(&key.public_key, base.as_bytes(), signature)
+        {
+            return Err(SignatureError::Invalid);
+        }
+
+        // Nonces are only recorded for valid signatures, so forged requests
+        // cannot burn an integration's nonces
+        let nonce = params.nonce().unwrap_or_default();
+        let uses = self
+            .cache
+            .increment_window(
+                format!(
+                    "http_signature_nonce:{}:{}",
+                    key.id,
+                    hex_digest(nonce.as_bytes())
+                ),
+                1,
+                MAX_SIGNATURE_AGE + Duration::from_secs(MAX_CLOCK_SKEW_SECS as u64),
+            )
+            .await
+            .map_err(|e| {
+                tracing::warn!(error = %e, "Failed to record HTTP signature nonce");
+                SignatureError::Unavailable
+            })?;
+        if uses > 1 {
+            return Err(SignatureError::Replayed);
+        }
+
+        self.touch(key.id).await;
+
+        Ok(VerifiedSignature {
+            key: key.id,
+            key_id: key.key_id.clone(),
+            name: key.name.clone(),
+            label,
+            components: params.components.clone(),
+            created: DateTime::from_timestamp(params.created().unwrap_or_default(), 0)
+                .unwrap_or(now),
+            user_id: key.created_by,
+        })
+    }
+
+    /// Record that a key was used, at most once a minute
+    async fn touch(&self, id: Uuid) {
+        let result = sqlx::query(
+            r#"
+            UPDATE http_signature_keys SET last_used_at = NOW()
+            WHERE id = $1
+              AND (last_used_at IS NULL OR last_used_at < NOW() - INTERVAL '1 minute')
+            "#,
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await;
+        if let Err(e) = result {
+            tracing::debug!(error = %e, key = %id, "Failed to record signature key use");
+        }
+    }
+
+    /// The newest active outbound key and its private half
+    async fn outbound_signer(&self) -> Result<Option<(HttpSignatureKey, SigningKey)>> {
+        let row: Option<(Vec<u8>, HttpSignatureKey)> = sqlx::query(&format!(
+            r#"
+            SELECT private_key, {} FROM http_signature_keys
+            WHERE direction = 'outbound' AND revoked_at IS NULL
+              AND (expires_at IS NULL OR expires_at > NOW())
+              AND private_key IS NOT NULL
+            ORDER BY created_at DESC
+            LIMIT 1
+            "#,
+            KEY_COLUMNS
+        ))
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load signing key", e))?
+        .map(|row| {
+            use sqlx::Row;
+            Ok::<_, sqlx::Error>((
+                row.try_get("private_key")?,
+                HttpSignatureKey::from_row(&row)?,
+            ))
+        })
+        .transpose()
+        .map_err(|e| Error::database_with_source("Failed to load signing key", e))?;
+
+        let Some((sealed, key)) = row else {
+            return Ok(None);
+        };
+        let pkcs8 = self.sealer.open(&sealed).ok_or_else(|| {
+            Error::internal("Signing key cannot be unsealed; the site secret has changed")
+        })?;
+        let signer = SigningKey::from_pkcs8(key.algorithm, &pkcs8)?;
+        Ok(Some((key, signer)))
+    }
+
+    /// Sign an outbound request with the newest outbound key, adding
+    /// `Content-Digest`, `Signature-Input` and `Signature` headers. Returns
+    /// `false`, leaving the request untouched, when no outbound key exists.
+    pub async fn sign_request(&self, request: &mut reqwest::Request) -> Result<bool> {
+        use reqwest::header::HeaderValue;
+
+        let Some((key, signer)) = self.outbound_signer().await? else {
+            return Ok(false);
+        };
+
+        let body = match request.body() {
+            Some(body) => Some(body.as_bytes().ok_or_else(|| {
+                Error::invalid_input("body", "Streaming bodies cannot be signed")
+            })?),
+            None => None,
+        }
+        .filter(|body| !body.is_empty());
+        if let Some(body) = body {
+            let digest = HeaderValue::from_str(&content_digest(body))
+                .map_err(|_| Error::internal("Invalid Content-Digest header"))?;
+            request.headers_mut().insert("content-digest", digest);
+        }
+
+        let url = request.url();
+        let authority = match url.port() {
+            Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+            None => url.host_str().unwrap_or_default().to_string(),
+        };
+        let target = SignatureTarget::new(
+            request.method().as_str(),
+            url.scheme(),
+            authority,
+            url.path(),
+            url.query().map(str::to_string),
+        )
+        .with_fields(
+            request
+                .headers()
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.as_bytes())),
+        );
+
+        let mut components = vec!["@method".to_string(), "@target-uri".to_string()];
+        for field in ["content-type", "content-digest"] {
+            if target.field(field).is_some() {
+                components.push(field.to_string());
+            }
+        }
+        let mut nonce = [0u8; 16];
+        OsRng.fill_bytes(&mut nonce);
+        let params = SignatureParams {
+            components,
+            params: vec![
+                (
+                    "created".to_string(),
+                    BareItem::Integer(Utc::now().timestamp()),
+                ),
+                ("keyid".to_string(), BareItem::String(key.key_id.clone())),
+                (
+                    "alg".to_string(),
+                    BareItem::String(key.algorithm.as_str().to_string()),
+                ),
+                (
+                    "nonce".to_string(),
+                    BareItem::String(URL_SAFE_NO_PAD.encode(nonce)),
+                ),
+            ],
+        };
+        let base = target
+            .signature_base(&params)
+            .map_err(|e| Error::internal(e.to_string()))?;
+        let signature = signer.sign(base.as_bytes())?;
+
+        let header = |value: String| {
+            HeaderValue::from_str(&value).map_err(|_| Error::internal("Invalid signature header"))
+        };
+        let headers = request.headers_mut();
+        headers.insert(
+            "signature-input",
+            header(format!("{}={}", OUTBOUND_LABEL, params.serialize()))?,
+        );
+        headers.insert(
+            "signature",
+            header(format!(
+                "{}=:{}:",
+                OUTBOUND_LABEL,
+                STANDARD.encode(signature)
+            ))?,
+        );
+        Ok(true)
+    }
+
+    /// The active user a verified signature acts for, if there still is one
+    pub async fn key_owner(
+        &self,
+        signature: &VerifiedSignature,
+    ) -> Result<Option<SignatureKeyOwner>> {
+        let Some(user_id) = signature.user_id else {
+            return Ok(None);
+        };
+        sqlx::query_as::<_, SignatureKeyOwner>(
+            "SELECT id AS user_id, email, role FROM users \
+             WHERE id = $1 AND status = 'active' AND deleted_at IS NULL",
+        )
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load signing key owner", e))
+    }
+
+    /// JWK set of the active outbound keys, for receivers to verify with
+    pub async fn signature_directory(&self) -> Result<serde_json::Value> {
+        let now = Utc::now();
+        let keys: Vec<serde_json::Value> = self
+            .list_keys(Some(KeyDirection::Outbound))
+            .await?
+            .iter()
+            .filter(|key| key.is_active(now))
+            .filter_map(HttpSignatureKey::jwk)
+            .collect();
+        Ok(serde_json::json!({ "keys": keys }))
+    }
+}
+
+fn validate_name(name: &str) -> Result<&str> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > MAX_KEY_NAME_LENGTH {
+        return Err(Error::invalid_input(
+            "name",
+            format!("Name must be 1 to {} characters", MAX_KEY_NAME_LENGTH),
+        ));
+    }
+    Ok(name)
+}
+
+fn hex_digest(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Request and key from RFC 9421 appendix B.2.6
+    fn rfc_request() -> SignatureTarget {
+        SignatureTarget::new(
+            "POST",
+            "https",
+            "example.com",
+            "/foo",
+            Some("param=Value&Pet=dog".into()),
+        )
+        .with_fields([
+            ("Host", b"example.com".as_slice()),
+            ("Date", b"Tue, 20 Apr 2021 02:07:55 GMT".as_slice()),
+            ("Content-Type", b"application/json".as_slice()),
+            ("Content-Length", b"18".as_slice()),
+        ])
+    }
+
+    const RFC_ED25519_PUBLIC_KEY: &str = "-----BEGIN PUBLIC KEY-----
+MCowBQYDK2VwAyEAJrQLj5P/89iXES9+vFgrIy29clF9CC/oPPsw3c5D0bs=
+-----END PUBLIC KEY-----";
+
+    #[test]
+    fn test_rfc_9421_ed25519_example() {
+        let input = r#"sig-b26=("date" "@method" "@path" "@authority" "content-type" "content-length");created=1618884473;keyid="test-key-ed25519""#;
+        let signature = "sig-b26=:wqcAqbmYJ2ji2glfAMaRy4gruYYnx2nEFN2HN6jrnDnQCK1u02Gb04v9EDgwUPiu4A0w6vuQv5lIp5WPpBKRCw==:";
+
+        let (label, params) = parse_signature_input(input).unwrap().remove(0);
+        assert_eq!(label, "sig-b26");
+        assert_eq!(format!("{}={}", label, params.serialize()), input);
+        let (_, sig) = parse_byte_dictionary(signature).unwrap().remove(0);
+
+        let base = rfc_request().signature_base(&params).unwrap();
+        assert_eq!(
+            base,
+            "\"date\": Tue, 20 Apr 2021 02:07:55 GMT\n\
+             \"@method\": POST\n\
+             \"@path\": /foo\n\
+             \"@authority\": example.com\n\
+             \"content-type\": application/json\n\
+             \"content-length\": 18\n\
+             \"@signature-params\": (\"date\" \"@method\" \"@path\" \"@authority\" \"content-type\" \"content-length\");created=1618884473;keyid=\"test-key-ed25519\""
+        );
+
+        let key = decode_public_key(SignatureAlgorithm::Ed25519, RFC_ED25519_PUBLIC_KEY).unwrap();
+        assert!(SignatureAlgorithm::Ed25519.verify(&key, base.as_bytes(), &sig));
+        assert!(!SignatureAlgorithm::Ed25519.verify(&key, b"tampered", &sig));
+    }
+
+    #[test]
+    fn test_generated_keys_sign_and_sealed_keys_open() {
+        for algorithm in [
+            SignatureAlgorithm::Ed25519,
+            SignatureAlgorithm::EcdsaP256Sha256,
+        ] {
+            let (pkcs8, public_key) = SigningKey::generate(algorithm).unwrap();
+            let sealer = KeySealer::new("secret");
+            let sealed = sealer.seal(&pkcs8).unwrap();
+            assert_ne!(sealed, pkcs8);
+            assert!(KeySealer::new("other").open(&sealed).is_none());
+
+            let signer = SigningKey::from_pkcs8(algorithm, &sealer.open(&sealed).unwrap()).unwrap();
+            let signature = signer.sign(b"message").unwrap();
+            assert!(algorithm.verify(&public_key, b"message", &signature));
+        }
+        assert!(SigningKey::generate(SignatureAlgorithm::RsaPssSha512).is_err());
+    }
+
+    #[test]
+    fn test_signature_params_policy() {
+        let now = 1_700_000_000;
+        let params = |input: &str| parse_signature_input(input).unwrap().remove(0).1;
+
+        let good = params(&format!(
+            r#"sig1=("@method" "@target-uri" "content-digest");created={};keyid="k";nonce="n""#,
+            now - 10
+        ));
+        assert_eq!(check_signature_params(&good, true, now), Ok(()));
+
+        let no_digest = params(&format!(
+            r#"sig1=("@method" "@target-uri");created={};keyid="k";nonce="n""#,
+            now
+        ));
+        assert_eq!(check_signature_params(&no_digest, false, now), Ok(()));
+        assert_eq!(
+            check_signature_params(&no_digest, true, now),
+            Err(SignatureError::MissingComponent("content-digest"))
+        );
+
+        let no_nonce = params(&format!(
+            r#"sig1=("@method" "@target-uri");created={};keyid="k""#,
+            now
+        ));
+        assert_eq!(
+            check_signature_params(&no_nonce, false, now),
+            Err(SignatureError::MissingParameter("nonce"))
+        );
+
+        let old = params(r#"sig1=("@method" "@target-uri");created=1;keyid="k";nonce="n""#);
+        assert_eq!(
+            check_signature_params(&old, false, now),
+            Err(SignatureError::Stale)
+        );
+
+        let path_only = params(&format!(
+            r#"sig1=("@method" "@path");created={};keyid="k";nonce="n""#,
+            now
+        ));
+        assert!(matches!(
+            check_signature_params(&path_only, false, now),
+            Err(SignatureError::MissingComponent(_))
+        ));
+    }
+
+    #[test]
+    fn test_parsing_and_digests() {
+        assert!(parse_signature_input(r#"sig1=("@query-param";name="id");created=1"#).is_none());
+        assert!(parse_signature_input("sig1=@method").is_none());
+        assert!(parse_byte_dictionary("sig1=:not base64!:").is_none());
+
+        let target = SignatureTarget::new("GET", "HTTPS", "Example.COM", "", None);
+        let params = parse_signature_input(r#"s=("@target-uri" "@query" "x-missing")"#)
+            .unwrap()
+            .remove(0)
+            .1;
+        assert_eq!(
+            target.signature_base(&params),
+            Err(SignatureError::AbsentComponent("x-missing".to_string()))
+        );
+        assert_eq!(
+            target.component("@target-uri").unwrap(),
+            "https://example.com/"
+        );
+        assert_eq!(target.component("@query").unwrap(), "?");
+
+        let body = br#"{"hello": "world"}"#;
+        let digest = content_digest(body);
+        assert_eq!(
+            digest,
+            "sha-256=:X48E9qOokqqrvdts8nOJRJN3OWDUoyWxBf7kbu9DBPE=:"
+        );
+        assert!(digest_matches(&digest, body));
+        assert!(!digest_matches(&digest, b"other"));
+        assert!(!digest_matches("md5=:AAAA:", body));
+    }
+}
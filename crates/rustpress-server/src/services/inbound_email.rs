@@ -0,0 +1,2237 @@
+//! Inbound Email
+//!
+//! Authors write posts by sending mail to a site's mailbox. Each mailbox
+//! belongs to one network site and is fed in one of three ways:
+//!
+//! - `imap`: the mailbox is polled every minute by a [`PollInboundEmailJob`];
+//!   unseen messages are fetched and marked seen
+//! - `mailgun`: a Mailgun route forwards messages to
+//!   `/inbound-email/mailgun/{token}/mime`, signed with the mailbox's
+//!   webhook signing key
+//! - `ses`: an SES receipt rule publishes messages to the mailbox's SNS
+//!   topic, which is subscribed to `/inbound-email/ses/{token}`; SNS
+//!   messages must carry a valid signature
+//!
+//! Every message becomes at most one draft post. The sender must be an
+//! active user whose role can create posts, mail that the receiving server
+//! didn't find to pass DMARC is refused, and a mailbox can further restrict
+//! who may post. Signatures and quoted replies are cut from the body, which
+//! is then sanitized with the fallback policy, as anyone can send mail.
+//! Attachments are saved to
+//! the media library: images referenced inline keep their place, other
+//! images and files are added below the text, and the first image becomes
+//! the featured image. Messages are logged once by their Message-ID, so
+//! messages seen again are ignored.
+
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use async_trait::async_trait;
+use base64::engine::general_purpose::{GeneralPurpose, GeneralPurposeConfig};
+use base64::engine::DecodePaddingMode;
+use base64::Engine as _;
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use encoding_rs::{Encoding, UTF_8};
+use parking_lot::Mutex;
+use ring::{hmac, signature};
+use rustpress_api::services::media_service::{MediaService, UploadMediaMetadata};
+use rustpress_api::services::post_service::{CreatePostRequest, PostService};
+use rustpress_auth::PermissionChecker;
+use rustpress_core::error::{Error, Result};
+use rustpress_core::http::HttpClient;
+use rustpress_core::tenant::{current_site, with_site};
+use rustpress_core::types::Slug;
+use rustpress_database::repository::posts::PostRepository;
+use rustpress_jobs::{JobHandler, JobPayload};
+use rustpress_storage::Storage;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use sqlx::{FromRow, PgPool};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufStream};
+use tokio::net::TcpStream;
+use uuid::Uuid;
+
+use super::content_sanitization::{ContentSanitizationService, ContentSource};
+use super::http_signatures::certificate_public_key;
+use super::wordpress_import::{media_type, truncate};
+
+/// Largest SNS signing certificate read
+const MAX_SNS_CERTIFICATE_SIZE: usize = 16 * 1024;
+
+/// SNS signing keys kept before the cache is emptied
+const MAX_SNS_CERTIFICATES: usize = 16;
+
+/// Largest message turned into a post
+pub const MAX_MESSAGE_SIZE: usize = 25 * 1024 * 1024;
+
+/// Attachments saved from one message
+const MAX_ATTACHMENTS: usize = 20;
+
+/// Storage directory of saved attachments
+const MEDIA_DIRECTORY: &str = "inbound-email";
+
+/// Messages fetched from an IMAP mailbox per poll
+const MESSAGES_PER_POLL: usize = 25;
+
+/// How long an IMAP server has to answer
+const IMAP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Longest IMAP response line
+const MAX_IMAP_LINE: u64 = 64 * 1024;
+
+/// How old a Mailgun signature may be
+const MAILGUN_SIGNATURE_MAX_AGE: i64 = 15 * 60;
+
+/// Nesting of multipart bodies that is followed
+const MAX_MIME_DEPTH: usize = 10;
+
+/// Entries of a mailbox's allowed sender list
+const MAX_ALLOWED_SENDERS: usize = 100;
+
+/// Random bytes of a webhook token
+const TOKEN_BYTES: usize = 24;
+
+/// Longest post title taken from a subject
+const MAX_TITLE_LENGTH: usize = 200;
+
+/// Base64 that accepts missing padding, as mail clients send it
+const LENIENT_BASE64: GeneralPurpose = GeneralPurpose::new(
+    &base64::alphabet::STANDARD,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+/// How messages reach a mailbox
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MailboxKind {
+    Imap,
+    Mailgun,
+    Ses,
+}
+
+impl MailboxKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Imap => "imap",
+            Self::Mailgun => "mailgun",
+            Self::Ses => "ses",
+        }
+    }
+}
+
+/// A mailbox whose messages become draft posts
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct InboundMailbox {
+    pub id: Uuid,
+    pub site_id: Option<Uuid>,
+    pub name: String,
+    pub kind: String,
+    /// Secret part of the webhook URL of Mailgun and SES mailboxes
+    pub token: String,
+    pub imap_host: Option<String>,
+    pub imap_port: i32,
+    pub imap_tls: bool,
+    pub imap_username: Option<String>,
+    #[serde(skip_serializing)]
+    pub imap_password: Option<String>,
+    pub imap_folder: String,
+    #[serde(skip_serializing)]
+    pub signing_key: Option<String>,
+    pub ses_topic_arn: Option<String>,
+    pub allowed_senders: Vec<String>,
+    pub category_ids: Vec<Uuid>,
+    pub is_active: bool,
+    pub last_polled_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    pub created_by: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl InboundMailbox {
+    /// Whether a sender address may post here; an empty list allows anyone
+    /// who can create posts
+    pub fn allows(&self, sender: &str) -> bool {
+        let sender = sender.to_lowercase();
+        self.allowed_senders.is_empty()
+            || self.allowed_senders.iter().any(|allowed| {
+                if allowed.starts_with('@') {
+                    sender.ends_with(allowed.as_str())
+                } else {
+                    sender == *allowed
+                }
+            })
+    }
+}
+
+/// A new mailbox
+#[derive(Debug, Deserialize)]
+pub struct InboundMailboxInput {
+    pub name: String,
+    pub kind: MailboxKind,
+    #[serde(flatten)]
+    pub settings: InboundMailboxUpdate,
+}
+
+/// Mailbox changes; missing fields are kept and empty strings clear
+/// optional ones
+#[derive(Debug, Default, Deserialize)]
+pub struct InboundMailboxUpdate {
+    pub name: Option<String>,
+    pub imap_host: Option<String>,
+    pub imap_port: Option<i32>,
+    pub imap_tls: Option<bool>,
+    pub imap_username: Option<String>,
+    pub imap_password: Option<String>,
+    pub imap_folder: Option<String>,
+    pub signing_key: Option<String>,
+    pub ses_topic_arn: Option<String>,
+    pub allowed_senders: Option<Vec<String>>,
+    pub category_ids: Option<Vec<Uuid>>,
+    pub is_active: Option<bool>,
+}
+
+/// What became of a received message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InboundMessageStatus {
+    Processing,
+    Created,
+    Rejected,
+    Failed,
+}
+
+impl InboundMessageStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Processing => "processing",
+            Self::Created => "created",
+            Self::Rejected => "rejected",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+/// A message received by a mailbox
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct InboundMessage {
+    pub id: Uuid,
+    pub mailbox_id: Uuid,
+    pub message_id: String,
+    pub sender: Option<String>,
+    pub subject: Option<String>,
+    pub status: String,
+    pub post_id: Option<Uuid>,
+    pub author_id: Option<Uuid>,
+    pub attachments: i32,
+    pub error: Option<String>,
+    pub received_at: DateTime<Utc>,
+}
+
+/// Message list filter
+#[derive(Debug, Default, Deserialize)]
+pub struct InboundMessageQuery {
+    pub status: Option<InboundMessageStatus>,
+    pub limit: Option<i64>,
+}
+
+/// Outcome of polling the IMAP mailboxes
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct PollReport {
+    pub mailboxes: usize,
+    pub received: usize,
+    pub failed: usize,
+}
+
+/// A parsed message
+#[derive(Debug, Default)]
+pub struct ParsedEmail {
+    /// Top-level headers in order, values unfolded but not decoded
+    pub headers: Vec<(String, String)>,
+    pub message_id: Option<String>,
+    /// Sender address, lowercased
+    pub from: Option<String>,
+    pub subject: String,
+    pub text: Option<String>,
+    pub html: Option<String>,
+    pub attachments: Vec<EmailAttachment>,
+}
+
+impl ParsedEmail {
+    /// First header of a name
+    pub fn header(&self, name: &str) -> Option<&str> {
+        header(&self.headers, name)
+    }
+
+    /// Whether the receiving server found the sender address to pass
+    /// DMARC, so that an aligned SPF or DKIM check vouches for its domain
+    ///
+    /// Only the topmost `Authentication-Results`, added by the receiving
+    /// server, is read; senders can add their own further down.
+    pub fn authentication_passed(&self) -> bool {
+        self.header("authentication-results")
+            .map(|results| {
+                results
+                    .split(';')
+                    .skip(1)
+                    .any(|result| result.trim().to_lowercase().starts_with("dmarc=pass"))
+            })
+            .unwrap_or(false)
+    }
+}
+
+/// A file attached to a message
+#[derive(Debug, Clone)]
+pub struct EmailAttachment {
+    pub filename: String,
+    pub content_type: String,
+    /// `Content-ID` that the HTML body refers to as `cid:...`
+    pub content_id: Option<String>,
+    pub data: Bytes,
+}
+
+/// Result of turning a message into a post
+enum DraftOutcome {
+    Created {
+        post_id: Uuid,
+        author_id: Uuid,
+        attachments: i32,
+    },
+    Rejected(&'static str),
+}
+
+/// An attachment saved to the media library
+struct SavedAttachment {
+    media_id: Uuid,
+    url: String,
+    filename: String,
+    content_id: Option<String>,
+    is_image: bool,
+}
+
+/// Inbound mailboxes and the posts created from their messages
+pub struct InboundEmailService {
+    pool: PgPool,
+    storage: Arc<Storage>,
+    sanitization: Arc<ContentSanitizationService>,
+    permissions: Arc<PermissionChecker>,
+    http: HttpClient,
+    /// Public keys of SNS signing certificates by URL
+    sns_keys: Mutex<HashMap<String, Arc<Vec<u8>>>>,
+}
+
+impl InboundEmailService {
+    pub fn new(
+        pool: PgPool,
+        storage: Arc<Storage>,
+        sanitization: Arc<ContentSanitizationService>,
+        permissions: Arc<PermissionChecker>,
+        http: HttpClient,
+    ) -> Self {
+        Self {
+            pool,
+            storage,
+            sanitization,
+            permissions,
+            http,
+            sns_keys: Mutex::default(),
+        }
+    }
+
+    /// Mailboxes of the current site
+    pub async fn list(&self) -> Result<Vec<InboundMailbox>> {
+        sqlx::query_as::<_, InboundMailbox>(
+            r#"
+            SELECT * FROM inbound_mailboxes
+            WHERE site_id IS NOT DISTINCT FROM $1
+            ORDER BY created_at
+            "#,
+        )
+        .bind(current_site())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to list inbound mailboxes", e))
+    }
+
+    pub async fn get(&self, id: Uuid) -> Result<InboundMailbox> {
+        sqlx::query_as::<_, InboundMailbox>(
+            "SELECT * FROM inbound_mailboxes WHERE id = $1 AND site_id IS NOT DISTINCT FROM $2",
+        )
+        .bind(id)
+        .bind(current_site())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load inbound mailbox", e))?
+        .ok_or_else(|| Error::not_found("Inbound mailbox", id))
+    }
+
+    /// The active webhook mailbox a token belongs to, on any site
+    pub async fn by_token(&self, kind: MailboxKind, token: &str) -> Result<InboundMailbox> {
+        sqlx::query_as::<_, InboundMailbox>(
+            "SELECT * FROM inbound_mailboxes WHERE token = $1 AND kind = $2 AND is_active",
+        )
+        .bind(token)
+        .bind(kind.as_str())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load inbound mailbox", e))?
+        .ok_or_else(|| Error::not_found("Inbound mailbox", token))
+    }
+
+    /// Add a mailbox to the current site
+    pub async fn create(
+        &self,
+        created_by: Uuid,
+        input: InboundMailboxInput,
+    ) -> Result<InboundMailbox> {
+        let now = Utc::now();
+        let mut mailbox = InboundMailbox {
+            id: Uuid::new_v4(),
+            site_id: current_site(),
+            name: input.name,
+            kind: input.kind.as_str().to_string(),
+            token: generate_token(),
+            imap_host: None,
+            imap_port: 993,
+            imap_tls: true,
+            imap_username: None,
+            imap_password: None,
+            imap_folder: "INBOX".to_string(),
+            signing_key: None,
+            ses_topic_arn: None,
+            allowed_senders: Vec::new(),
+            category_ids: Vec::new(),
+            is_active: true,
+            last_polled_at: None,
+            last_error: None,
+            created_by,
+            created_at: now,
+            updated_at: now,
+        };
+        apply_update(&mut mailbox, input.settings)?;
+
+        sqlx::query_as::<_, InboundMailbox>(
+            r#"
+            INSERT INTO inbound_mailboxes (
+                id, site_id, name, kind, token, imap_host, imap_port, imap_tls,
+                imap_username, imap_password, imap_folder, signing_key, ses_topic_arn,
+                allowed_senders, category_ids, is_active, created_by
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17)
+            RETURNING *
+            "#,
+        )
+        .bind(mailbox.id)
+        .bind(mailbox.site_id)
+        .bind(&mailbox.name)
+        .bind(&mailbox.kind)
+        .bind(&mailbox.token)
+        .bind(&mailbox.imap_host)
+        .bind(mailbox.imap_port)
+        .bind(mailbox.imap_tls)
+        .bind(&mailbox.imap_username)
+        .bind(&mailbox.imap_password)
+        .bind(&mailbox.imap_folder)
+        .bind(&mailbox.signing_key)
+        .bind(&mailbox.ses_topic_arn)
+        .bind(&mailbox.allowed_senders)
+        .bind(&mailbox.category_ids)
+        .bind(mailbox.is_active)
+        .bind(mailbox.created_by)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to create inbound mailbox", e))
+    }
+
+    pub async fn update(&self, id: Uuid, update: InboundMailboxUpdate) -> Result<InboundMailbox> {
+        let mut mailbox = self.get(id).await?;
+        apply_update(&mut mailbox, update)?;
+
+        sqlx::query_as::<_, InboundMailbox>(
+            r#"
+            UPDATE inbound_mailboxes
+            SET name = $2, imap_host = $3, imap_port = $4, imap_tls = $5, imap_username = $6,
+                imap_password = $7, imap_folder = $8, signing_key = $9, ses_topic_arn = $10,
+                allowed_senders = $11, category_ids = $12, is_active = $13, updated_at = NOW()
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(&mailbox.name)
+        .bind(&mailbox.imap_host)
+        .bind(mailbox.imap_port)
+        .bind(mailbox.imap_tls)
+        .bind(&mailbox.imap_username)
+        .bind(&mailbox.imap_password)
+        .bind(&mailbox.imap_folder)
+        .bind(&mailbox.signing_key)
+        .bind(&mailbox.ses_topic_arn)
+        .bind(&mailbox.allowed_senders)
+        .bind(&mailbox.category_ids)
+        .bind(mailbox.is_active)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to update inbound mailbox", e))
+    }
+
+    /// Replace a mailbox's webhook token; the old URL stops working
+    pub async fn rotate_token(&self, id: Uuid) -> Result<InboundMailbox> {
+        self.get(id).await?;
+        sqlx::query_as::<_, InboundMailbox>(
+            "UPDATE inbound_mailboxes SET token = $2, updated_at = NOW() WHERE id = $1 RETURNING *",
+        )
+        .bind(id)
+        .bind(generate_token())
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to rotate inbound mailbox token", e))
+    }
+
+    /// Remove a mailbox and its message log; posts created stay
+    pub async fn delete(&self, id: Uuid) -> Result<()> {
+        self.get(id).await?;
+        sqlx::query("DELETE FROM inbound_mailboxes WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to delete inbound mailbox", e))?;
+        Ok(())
+    }
+
+    /// Newest messages received by a mailbox
+    pub async fn messages(
+        &self,
+        mailbox_id: Uuid,
+        query: &InboundMessageQuery,
+    ) -> Result<Vec<InboundMessage>> {
+        sqlx::query_as::<_, InboundMessage>(
+            r#"
+            SELECT * FROM inbound_messages
+            WHERE mailbox_id = $1 AND ($2::varchar IS NULL OR status = $2)
+            ORDER BY received_at DESC
+            LIMIT $3
+            "#,
+        )
+        .bind(mailbox_id)
+        .bind(query.status.map(|s| s.as_str()))
+        .bind(query.limit.unwrap_or(50).clamp(1, 200))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to list inbound messages", e))
+    }
+
+    /// Turn a raw RFC 5322 message into a draft post on the mailbox's site
+    ///
+    /// Messages that can't become a post are logged as rejected or failed;
+    /// only storage and database trouble is returned as an error.
+    pub async fn receive(&self, mailbox: &InboundMailbox, raw: &[u8]) -> Result<InboundMessage> {
+        with_site(mailbox.site_id, self.receive_on_site(mailbox, raw)).await
+    }
+
+    async fn receive_on_site(
+        &self,
+        mailbox: &InboundMailbox,
+        raw: &[u8],
+    ) -> Result<InboundMessage> {
+        let email = parse_message(raw);
+        let message_id = email
+            .message_id
+            .clone()
+            .unwrap_or_else(|| content_hash(raw));
+
+        let logged = sqlx::query_as::<_, InboundMessage>(
+            r#"
+            INSERT INTO inbound_messages (mailbox_id, message_id, sender, subject)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (mailbox_id, message_id) DO NOTHING
+            RETURNING *
+            "#,
+        )
+        .bind(mailbox.id)
+        .bind(&message_id)
+        .bind(email.from.as_deref())
+        .bind(truncate(&email.subject, 1000))
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to log inbound message", e))?;
+        let Some(logged) = logged else {
+            // Seen before, e.g. a webhook retried or a poll that was cut off
+            return sqlx::query_as::<_, InboundMessage>(
+                "SELECT * FROM inbound_messages WHERE mailbox_id = $1 AND message_id = $2",
+            )
+            .bind(mailbox.id)
+            .bind(&message_id)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to load inbound message", e));
+        };
+
+        let outcome = if raw.len() > MAX_MESSAGE_SIZE {
+            Ok(DraftOutcome::Rejected("The message is too large"))
+        } else {
+            self.create_draft(mailbox, &email).await
+        };
+        let (status, post_id, author_id, attachments, error) = match outcome {
+            Ok(DraftOutcome::Created {
+                post_id,
+                author_id,
+                attachments,
+            }) => (
+                InboundMessageStatus::Created,
+                Some(post_id),
+                Some(author_id),
+                attachments,
+                None,
+            ),
+            Ok(DraftOutcome::Rejected(reason)) => (
+                InboundMessageStatus::Rejected,
+                None,
+                None,
+                0,
+                Some(reason.to_string()),
+            ),
+            Err(e) => {
+                tracing::warn!(
+                    mailbox_id = %mailbox.id,
+                    message_id = %message_id,
+                    "Failed to create a post from an inbound message: {}",
+                    e
+                );
+                (
+                    InboundMessageStatus::Failed,
+                    None,
+                    None,
+                    0,
+                    Some(e.to_string()),
+                )
+            }
+        };
+
+        sqlx::query_as::<_, InboundMessage>(
+            r#"
+            UPDATE inbound_messages
+            SET status = $2, post_id = $3, author_id = $4, attachments = $5, error = $6
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(logged.id)
+        .bind(status.as_str())
+        .bind(post_id)
+        .bind(author_id)
+        .bind(attachments)
+        .bind(error)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to update inbound message", e))
+    }
+
+    /// Create a draft from a parsed message, if its sender may post
+    async fn create_draft(
+        &self,
+        mailbox: &InboundMailbox,
+        email: &ParsedEmail,
+    ) -> Result<DraftOutcome> {
+        let Some(sender) = email.from.as_deref() else {
+            return Ok(DraftOutcome::Rejected("The message has no sender address"));
+        };
+        if !email.authentication_passed() {
+            return Ok(DraftOutcome::Rejected(
+                "The sender address did not pass DMARC",
+            ));
+        }
+        if !mailbox.allows(sender) {
+            return Ok(DraftOutcome::Rejected(
+                "The sender may not post to this mailbox",
+            ));
+        }
+        let author: Option<(Uuid, String)> = sqlx::query_as(
+            "SELECT id, role FROM users WHERE LOWER(email) = $1 \
+             AND status = 'active' AND deleted_at IS NULL",
+        )
+        .bind(sender)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to look up the sender", e))?;
+        let Some((author_id, role)) = author else {
+            return Ok(DraftOutcome::Rejected(
+                "No active user has the sender's address",
+            ));
+        };
+        let roles = vec![role];
+        if !self.permissions.can(&roles, "posts", "create") {
+            return Ok(DraftOutcome::Rejected("The sender can not create posts"));
+        }
+
+        let mut content = match (&email.html, &email.text) {
+            (Some(html), _) => strip_html_signature(html_body(html)),
+            (None, Some(text)) => text_to_html(&strip_signature(text)),
+            (None, None) => String::new(),
+        };
+
+        let mut saved = Vec::new();
+        for attachment in email.attachments.iter().take(MAX_ATTACHMENTS) {
+            match self.save_attachment(author_id, attachment).await {
+                Ok(attachment) => saved.push(attachment),
+                Err(e) => tracing::warn!(
+                    filename = %attachment.filename,
+                    "Failed to save an inbound email attachment: {}",
+                    e
+                ),
+            }
+        }
+        let featured_image_id = saved.iter().find(|a| a.is_image).map(|a| a.media_id);
+        for attachment in &saved {
+            if let Some(cid) = attachment
+                .content_id
+                .as_deref()
+                .map(|cid| format!("cid:{}", cid))
+                .filter(|cid| content.contains(cid.as_str()))
+            {
+                content = content.replace(&cid, &attachment.url);
+            } else if attachment.is_image {
+                content.push_str(&format!(
+                    "\n<p><img src=\"{}\" alt=\"{}\"></p>",
+                    escape_html(&attachment.url),
+                    escape_html(&attachment.filename)
+                ));
+            } else {
+                content.push_str(&format!(
+                    "\n<p><a href=\"{}\">{}</a></p>",
+                    escape_html(&attachment.url),
+                    escape_html(&attachment.filename)
+                ));
+            }
+        }
+
+        let sanitized = self.sanitization.sanitize_untrusted(&content).await;
+        let title = post_title(email);
+        let request = CreatePostRequest {
+            slug: Some(self.free_slug(&title).await?),
+            title,
+            excerpt: None,
+            content: Some(sanitized.html.clone()),
+            content_format: None,
+            status: Some("draft".to_string()),
+            visibility: None,
+            password: None,
+            featured_image_id,
+            comment_status: None,
+            ping_status: None,
+            published_at: None,
+            category_ids: Some(mailbox.category_ids.clone()).filter(|ids| !ids.is_empty()),
+            tag_ids: None,
+            scheduled_at: None,
+            expires_at: None,
+            embargo_starts_at: None,
+            embargo_ends_at: None,
+            timezone: None,
+        };
+        let post = PostService::new(self.pool.clone())
+            .create_post(request, author_id)
+            .await?;
+
+        self.sanitization
+            .record_quietly(
+                ContentSource::Post,
+                Some(post.id),
+                Some(author_id),
+                &sanitized,
+            )
+            .await;
+        if let Err(e) = MediaService::new(self.pool.clone())
+            .sync_usage("post", post.id, &sanitized.html, featured_image_id)
+            .await
+        {
+            tracing::warn!(post_id = %post.id, "Failed to record media usage: {}", e);
+        }
+
+        Ok(DraftOutcome::Created {
+            post_id: post.id,
+            author_id,
+            attachments: saved.len() as i32,
+        })
+    }
+
+    /// Save an attachment to storage and add it to the media library
+    async fn save_attachment(
+        &self,
+        uploaded_by: Uuid,
+        attachment: &EmailAttachment,
+    ) -> Result<SavedAttachment> {
+        let mime_type = media_type(Some(&attachment.content_type), &attachment.filename);
+        let stored = self
+            .storage
+            .upload_to(
+                attachment.data.clone(),
+                &attachment.filename,
+                &mime_type,
+                MEDIA_DIRECTORY,
+            )
+            .await?;
+        let media = MediaService::new(self.pool.clone())
+            .upload_media(
+                uploaded_by,
+                stored.filename.clone(),
+                truncate(&attachment.filename, 500),
+                mime_type.clone(),
+                attachment.data.len() as i64,
+                stored.path.clone(),
+                Some(UploadMediaMetadata {
+                    alt_text: None,
+                    title: Some(truncate(&attachment.filename, 500)),
+                    description: None,
+                    folder_id: None,
+                }),
+                None,
+                None,
+            )
+            .await;
+        let media = match media {
+            Ok(media) => media,
+            Err(e) => {
+                let _ = self.storage.delete(&stored.path).await;
+                return Err(e);
+            }
+        };
+
+        Ok(SavedAttachment {
+            media_id: media.id,
+            url: self
+                .storage
+                .url(&stored.path)
+                .unwrap_or_else(|| format!("/uploads/{}", stored.path)),
+            filename: attachment.filename.clone(),
+            content_id: attachment.content_id.clone(),
+            is_image: mime_type.starts_with("image/"),
+        })
+    }
+
+    /// The title's slug, numbered when a post already has it
+    async fn free_slug(&self, title: &str) -> Result<String> {
+        let base = Slug::new(title)
+            .as_str()
+            .chars()
+            .take(180)
+            .collect::<String>();
+        let base = if base.is_empty() {
+            "post".to_string()
+        } else {
+            base
+        };
+        let posts = PostRepository::new(self.pool.clone());
+        for n in 1..=20 {
+            let candidate = if n == 1 {
+                base.clone()
+            } else {
+                format!("{}-{}", base, n)
+            };
+            if posts.find_by_slug(&candidate).await?.is_none() {
+                return Ok(candidate);
+            }
+        }
+        Ok(format!(
+            "{}-{}",
+            base,
+            &Uuid::new_v4().simple().to_string()[..8]
+        ))
+    }
+
+    /// Check a Mailgun webhook signature, an HMAC-SHA256 of
+    /// `{timestamp}{token}` under the mailbox's signing key
+    pub fn verify_mailgun(
+        &self,
+        mailbox: &InboundMailbox,
+        timestamp: &str,
+        token: &str,
+        signature: &str,
+    ) -> Result<()> {
+        let Some(key) = mailbox.signing_key.as_deref() else {
+            return Err(Error::unauthorized("The mailbox has no signing key"));
+        };
+        verify_mailgun_signature(key, timestamp, token, signature, Utc::now().timestamp())
+    }
+
+    /// Handle an SNS message posted to an SES mailbox: confirm the
+    /// subscription, or receive the message a notification carries
+    pub async fn receive_ses(
+        &self,
+        mailbox: &InboundMailbox,
+        notification: &Value,
+    ) -> Result<Option<InboundMessage>> {
+        let Some(topic) = mailbox.ses_topic_arn.as_deref() else {
+            return Err(Error::forbidden("The mailbox has no SNS topic"));
+        };
+        if notification["TopicArn"].as_str() != Some(topic) {
+            return Err(Error::forbidden("Notification from another SNS topic"));
+        }
+        self.verify_sns(notification).await?;
+        match notification["Type"].as_str() {
+            Some("SubscriptionConfirmation") => {
+                let url = notification["SubscribeURL"].as_str().unwrap_or_default();
+                self.confirm_subscription(url).await?;
+                Ok(None)
+            }
+            Some("Notification") => {
+                let message: Value = notification["Message"]
+                    .as_str()
+                    .and_then(|message| serde_json::from_str(message).ok())
+                    .ok_or_else(|| Error::invalid_input("Message", "Not an SES notification"))?;
+                let raw = ses_content(&message)?;
+                self.receive(mailbox, &raw).await.map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Check the signature of an SNS message against the certificate it
+    /// names, which must be served by SNS
+    async fn verify_sns(&self, notification: &Value) -> Result<()> {
+        let forged = || Error::forbidden("Invalid SNS signature");
+        let message = sns_string_to_sign(notification).ok_or_else(forged)?;
+        let algorithm: &dyn signature::VerificationAlgorithm =
+            match notification["SignatureVersion"].as_str() {
+                Some("1") => &signature::RSA_PKCS1_2048_8192_SHA1_FOR_LEGACY_USE_ONLY,
+                Some("2") => &signature::RSA_PKCS1_2048_8192_SHA256,
+                _ => return Err(forged()),
+            };
+        let signature_value = notification["Signature"]
+            .as_str()
+            .and_then(|value| LENIENT_BASE64.decode(value).ok())
+            .ok_or_else(forged)?;
+        let url = sns_certificate_url(notification["SigningCertURL"].as_str().unwrap_or_default())?;
+        let key = self.sns_signing_key(url).await?;
+        signature::UnparsedPublicKey::new(algorithm, key.as_slice())
+            .verify(message.as_bytes(), &signature_value)
+            .map_err(|_| forged())
+    }
+
+    /// Public key of an SNS signing certificate, fetched once
+    async fn sns_signing_key(&self, url: reqwest::Url) -> Result<Arc<Vec<u8>>> {
+        if let Some(key) = self.sns_keys.lock().get(url.as_str()) {
+            return Ok(key.clone());
+        }
+
+        let failed = |e: &dyn std::fmt::Display| {
+            Error::internal(format!(
+                "Failed to fetch the SNS signing certificate: {}",
+                e
+            ))
+        };
+        let mut response = self
+            .http
+            .send(self.http.get(url.clone()).timeout(Duration::from_secs(15)))
+            .await
+            .map_err(|e| failed(&e))?
+            .error_for_status()
+            .map_err(|e| failed(&e))?;
+        let mut pem = Vec::new();
+        while let Some(chunk) = response.chunk().await.map_err(|e| failed(&e))? {
+            if pem.len() + chunk.len() > MAX_SNS_CERTIFICATE_SIZE {
+                return Err(failed(&"certificate too large"));
+            }
+            pem.extend_from_slice(&chunk);
+        }
+        let key = String::from_utf8_lossy(&pem)
+            .split("-----BEGIN CERTIFICATE-----")
+            .nth(1)
+            .and_then(|rest| rest.split("-----END CERTIFICATE-----").next())
+            .and_then(|body| {
+                LENIENT_BASE64
+                    .decode(body.split_whitespace().collect::<String>())
+                    .ok()
+            })
+            .and_then(|der| certificate_public_key(&der).map(|key| Arc::new(key.to_vec())))
+            .ok_or_else(|| failed(&"not an X.509 certificate"))?;
+
+        let mut keys = self.sns_keys.lock();
+        if keys.len() >= MAX_SNS_CERTIFICATES {
+            keys.clear();
+        }
+        keys.insert(url.to_string(), key.clone());
+        Ok(key)
+    }
+
+    /// Confirm an SNS subscription; only AWS URLs are followed
+    async fn confirm_subscription(&self, url: &str) -> Result<()> {
+        let parsed = reqwest::Url::parse(url)
+            .map_err(|_| Error::invalid_input("SubscribeURL", "Invalid subscription URL"))?;
+        let from_aws = parsed.scheme() == "https"
+            && parsed
+                .host_str()
+                .map(|host| host.ends_with(".amazonaws.com"))
+                .unwrap_or(false);
+        if !from_aws {
+            return Err(Error::invalid_input(
+                "SubscribeURL",
+                "Subscriptions are only confirmed with Amazon SNS",
+            ));
+        }
+        self.http
+            .send(self.http.get(parsed).timeout(Duration::from_secs(15)))
+            .await
+            .map_err(|e| Error::internal(format!("Subscription confirmation failed: {}", e)))?
+            .error_for_status()
+            .map_err(|e| Error::internal(format!("Subscription confirmation failed: {}", e)))?;
+        Ok(())
+    }
+
+    /// Fetch unseen messages from an IMAP mailbox, recording how it went
+    pub async fn poll(&self, mailbox: &InboundMailbox) -> Result<usize> {
+        let result = self.fetch_imap(mailbox).await;
+        let error = result.as_ref().err().map(|e| e.to_string());
+        sqlx::query(
+            "UPDATE inbound_mailboxes SET last_polled_at = NOW(), last_error = $2 WHERE id = $1",
+        )
+        .bind(mailbox.id)
+        .bind(error)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to update inbound mailbox", e))?;
+        result
+    }
+
+    /// Poll every active IMAP mailbox of every site
+    pub async fn poll_all(&self) -> Result<PollReport> {
+        let mailboxes = sqlx::query_as::<_, InboundMailbox>(
+            "SELECT * FROM inbound_mailboxes WHERE kind = 'imap' AND is_active ORDER BY created_at",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to list inbound mailboxes", e))?;
+
+        let mut report = PollReport::default();
+        for mailbox in &mailboxes {
+            report.mailboxes += 1;
+            match self.poll(mailbox).await {
+                Ok(received) => report.received += received,
+                Err(e) => {
+                    report.failed += 1;
+                    tracing::warn!(mailbox_id = %mailbox.id, "Failed to poll inbound mailbox: {}", e);
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    async fn fetch_imap(&self, mailbox: &InboundMailbox) -> Result<usize> {
+        let (Some(host), Some(username), Some(password)) = (
+            mailbox.imap_host.as_deref(),
+            mailbox.imap_username.as_deref(),
+            mailbox.imap_password.as_deref(),
+        ) else {
+            return Err(Error::invalid_input(
+                "imap_host",
+                "The mailbox has no IMAP server or login",
+            ));
+        };
+        let port = u16::try_from(mailbox.imap_port)
+            .map_err(|_| Error::invalid_input("imap_port", "Invalid IMAP port"))?;
+
+        let mut session = ImapSession::connect(host, port, mailbox.imap_tls).await?;
+        session.login(username, password).await?;
+        session.select(&mailbox.imap_folder).await?;
+        let uids = session.search_unseen().await?;
+
+        let mut received = 0;
+        for uid in uids.into_iter().take(MESSAGES_PER_POLL) {
+            let raw = session.fetch(uid).await?;
+            // Left unseen when it couldn't be logged, to be fetched again
+            self.receive(mailbox, &raw).await?;
+            session.mark_seen(uid).await?;
+            received += 1;
+        }
+        session.logout().await;
+        Ok(received)
+    }
+}
+
+/// The fields an SNS message's signature covers, as `name\nvalue\n` lines
+fn sns_string_to_sign(notification: &Value) -> Option<String> {
+    let fields: &[&str] = match notification["Type"].as_str()? {
+        "Notification" => &[
+            "Message",
+            "MessageId",
+            "Subject",
+            "Timestamp",
+            "TopicArn",
+            "Type",
+        ],
+        "SubscriptionConfirmation" | "UnsubscribeConfirmation" => &[
+            "Message",
+            "MessageId",
+            "SubscribeURL",
+            "Timestamp",
+            "Token",
+            "TopicArn",
+            "Type",
+        ],
+        _ => return None,
+    };
+    let mut signed = String::new();
+    for field in fields {
+        match notification[*field].as_str() {
+            Some(value) => {
+                signed.push_str(field);
+                signed.push('\n');
+                signed.push_str(value);
+                signed.push('\n');
+            }
+            // Notifications published without a subject leave it out
+            None if *field == "Subject" => {}
+            None => return None,
+        }
+    }
+    Some(signed)
+}
+
+/// A signing certificate URL served by SNS: `https://sns.{region}.amazonaws.com/...pem`
+fn sns_certificate_url(url: &str) -> Result<reqwest::Url> {
+    let invalid = || Error::forbidden("The SNS signing certificate is not served by SNS");
+    let parsed = reqwest::Url::parse(url).map_err(|_| invalid())?;
+    let region = parsed
+        .host_str()
+        .and_then(|host| host.strip_prefix("sns."))
+        .and_then(|host| {
+            host.strip_suffix(".amazonaws.com")
+                .or_else(|| host.strip_suffix(".amazonaws.com.cn"))
+        });
+    let from_sns = parsed.scheme() == "https"
+        && parsed.port().is_none()
+        && region.is_some_and(|region| {
+            !region.is_empty()
+                && region
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+        && parsed.path().ends_with(".pem");
+    if from_sns {
+        Ok(parsed)
+    } else {
+        Err(invalid())
+    }
+}
+
+/// Apply mailbox changes, checking the mailbox has what its kind needs
+fn apply_update(mailbox: &mut InboundMailbox, update: InboundMailboxUpdate) -> Result<()> {
+    fn optional(value: String) -> Option<String> {
+        Some(value.trim().to_string()).filter(|v| !v.is_empty())
+    }
+
+    if let Some(name) = update.name {
+        mailbox.name = name;
+    }
+    mailbox.name = mailbox.name.trim().to_string();
+    if mailbox.name.is_empty() || mailbox.name.len() > 255 {
+        return Err(Error::invalid_input(
+            "name",
+            "Name must be 1-255 characters",
+        ));
+    }
+    if let Some(host) = update.imap_host {
+        mailbox.imap_host = optional(host);
+    }
+    if let Some(port) = update.imap_port {
+        if !(1..=65535).contains(&port) {
+            return Err(Error::invalid_input("imap_port", "Invalid IMAP port"));
+        }
+        mailbox.imap_port = port;
+    }
+    if let Some(tls) = update.imap_tls {
+        mailbox.imap_tls = tls;
+    }
+    if let Some(username) = update.imap_username {
+        mailbox.imap_username = optional(username);
+    }
+    if let Some(password) = update.imap_password {
+        mailbox.imap_password = Some(password).filter(|p| !p.is_empty());
+    }
+    if let Some(folder) = update.imap_folder {
+        mailbox.imap_folder = optional(folder).unwrap_or_else(|| "INBOX".to_string());
+    }
+    if let Some(key) = update.signing_key {
+        mailbox.signing_key = optional(key);
+    }
+    if let Some(topic) = update.ses_topic_arn {
+        mailbox.ses_topic_arn = optional(topic);
+    }
+    if let Some(senders) = update.allowed_senders {
+        mailbox.allowed_senders = check_allowed_senders(senders)?;
+    }
+    if let Some(category_ids) = update.category_ids {
+        mailbox.category_ids = category_ids;
+    }
+    if let Some(is_active) = update.is_active {
+        mailbox.is_active = is_active;
+    }
+
+    for value in [&mailbox.imap_username, &mailbox.imap_password]
+        .into_iter()
+        .flatten()
+        .chain([&mailbox.imap_folder])
+    {
+        if value.contains(['\r', '\n']) || !value.is_ascii() {
+            return Err(Error::invalid_input(
+                "imap_username",
+                "IMAP login and folder must be ASCII without line breaks",
+            ));
+        }
+    }
+    match mailbox.kind.as_str() {
+        "imap"
+            if mailbox.imap_host.is_none()
+                || mailbox.imap_username.is_none()
+                || mailbox.imap_password.is_none() =>
+        {
+            Err(Error::invalid_input(
+                "imap_host",
+                "IMAP mailboxes need a server, username and password",
+            ))
+        }
+        "mailgun" if mailbox.signing_key.is_none() => Err(Error::invalid_input(
+            "signing_key",
+            "Mailgun mailboxes need the webhook signing key",
+        )),
+        "ses" if mailbox.ses_topic_arn.is_none() => Err(Error::invalid_input(
+            "ses_topic_arn",
+            "SES mailboxes need the ARN of their SNS topic",
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Lowercased addresses and `@domain`s, without duplicates
+fn check_allowed_senders(senders: Vec<String>) -> Result<Vec<String>> {
+    let mut allowed: Vec<String> = Vec::new();
+    for sender in senders {
+        let sender = sender.trim().to_lowercase();
+        if sender.is_empty() {
+            continue;
+        }
+        let valid = match sender.strip_prefix('@') {
+            Some(domain) => domain.contains('.') && !domain.contains('@'),
+            None => sender.split('@').count() == 2 && !sender.starts_with('@'),
+        };
+        if !valid || sender.len() > 255 {
+            return Err(Error::invalid_input(
+                "allowed_senders",
+                format!("'{}' is not an address or @domain", sender),
+            ));
+        }
+        if !allowed.contains(&sender) {
+            allowed.push(sender);
+        }
+    }
+    if allowed.len() > MAX_ALLOWED_SENDERS {
+        return Err(Error::invalid_input(
+            "allowed_senders",
+            format!("At most {} senders can be listed", MAX_ALLOWED_SENDERS),
+        ));
+    }
+    Ok(allowed)
+}
+
+fn generate_token() -> String {
+    let mut bytes = [0u8; TOKEN_BYTES];
+    OsRng.fill_bytes(&mut bytes);
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Stand-in Message-ID of a message without one
+fn content_hash(raw: &[u8]) -> String {
+    let digest = Sha256::digest(raw);
+    let hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("sha256:{}", hex)
+}
+
+fn verify_mailgun_signature(
+    key: &str,
+    timestamp: &str,
+    token: &str,
+    signature: &str,
+    now: i64,
+) -> Result<()> {
+    let signed_at: i64 = timestamp
+        .parse()
+        .map_err(|_| Error::unauthorized("Invalid Mailgun timestamp"))?;
+    if (now - signed_at).abs() > MAILGUN_SIGNATURE_MAX_AGE {
+        return Err(Error::unauthorized("Mailgun signature has expired"));
+    }
+    let signature =
+        decode_hex(signature).ok_or_else(|| Error::unauthorized("Invalid Mailgun signature"))?;
+    let key = hmac::Key::new(hmac::HMAC_SHA256, key.as_bytes());
+    hmac::verify(
+        &key,
+        format!("{}{}", timestamp, token).as_bytes(),
+        &signature,
+    )
+    .map_err(|_| Error::unauthorized("Invalid Mailgun signature"))
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// The raw message of an SES `Received` notification
+fn ses_content(message: &Value) -> Result<Vec<u8>> {
+    let content = message["content"].as_str().ok_or_else(|| {
+        Error::invalid_input(
+            "content",
+            "The SES notification carries no message; use an SNS action",
+        )
+    })?;
+    if message["receipt"]["action"]["encoding"].as_str() == Some("BASE64") {
+        LENIENT_BASE64
+            .decode(content.trim())
+            .map_err(|_| Error::invalid_input("content", "Invalid base64 message"))
+    } else {
+        Ok(content.as_bytes().to_vec())
+    }
+}
+
+/// The post title: the subject, or the start of the text without one
+fn post_title(email: &ParsedEmail) -> String {
+    let title = if email.subject.trim().is_empty() {
+        email
+            .text
+            .as_deref()
+            .and_then(|text| text.lines().map(str::trim).find(|line| !line.is_empty()))
+            .unwrap_or("Untitled")
+    } else {
+        email.subject.trim()
+    };
+    truncate(title, MAX_TITLE_LENGTH)
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Paragraphs of plain text as HTML
+pub fn text_to_html(text: &str) -> String {
+    text.split("\n\n")
+        .map(str::trim)
+        .filter(|paragraph| !paragraph.is_empty())
+        .map(|paragraph| {
+            let lines: Vec<String> = paragraph
+                .lines()
+                .map(|l| escape_html(l.trim_end()))
+                .collect();
+            format!("<p>{}</p>", lines.join("<br>\n"))
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Cut the signature, mobile footer and quoted reply from a plain text body
+pub fn strip_signature(text: &str) -> String {
+    let text = text.replace("\r\n", "\n");
+    let mut kept: Vec<&str> = Vec::new();
+    for line in text.lines() {
+        let trimmed = line.trim();
+        let lower = trimmed.to_lowercase();
+        let cut = trimmed == "--"
+            || trimmed.starts_with("_____")
+            || (trimmed.starts_with("-----") && lower.contains("original message"))
+            || lower.starts_with("sent from my ")
+            || lower.starts_with("sent from mail for ")
+            || lower.starts_with("get outlook for ");
+        if cut {
+            break;
+        }
+        if trimmed.ends_with("wrote:") {
+            if trimmed.starts_with("On ") {
+                break;
+            }
+            // Long reply headers are wrapped before `wrote:`
+            if kept
+                .last()
+                .is_some_and(|last| last.trim().starts_with("On "))
+            {
+                kept.pop();
+                break;
+            }
+        }
+        kept.push(line);
+    }
+    while kept
+        .last()
+        .is_some_and(|line| line.trim().is_empty() || line.trim_start().starts_with('>'))
+    {
+        kept.pop();
+    }
+    kept.join("\n")
+}
+
+/// Where mail clients start signatures and quoted replies in HTML
+const HTML_SIGNATURE_MARKERS: &[&str] = &[
+    "class=\"gmail_signature",
+    "data-smartmail=\"gmail_signature",
+    "class=\"gmail_quote",
+    "class=\"moz-signature",
+    "class=\"moz-cite-prefix",
+    "id=\"signature\"",
+    "id=\"divrplyfwdmsg\"",
+    "id=\"appendonsend\"",
+    "<blockquote type=\"cite\"",
+    ">sent from my ",
+    ">-- <br",
+];
+
+/// The inside of an HTML document's body
+pub fn html_body(html: &str) -> &str {
+    let lower = html.to_ascii_lowercase();
+    let Some(open) = lower.find("<body") else {
+        return html;
+    };
+    let Some(start) = lower[open..].find('>').map(|i| open + i + 1) else {
+        return html;
+    };
+    let end = lower
+        .rfind("</body")
+        .filter(|&end| end >= start)
+        .unwrap_or(html.len());
+    &html[start..end]
+}
+
+/// Cut the signature and quoted reply from an HTML body, at the element
+/// a known mail client starts them with; the sanitizer closes what is left
+/// open
+pub fn strip_html_signature(html: &str) -> String {
+    let lower = html.to_ascii_lowercase();
+    let cut = HTML_SIGNATURE_MARKERS
+        .iter()
+        .filter_map(|marker| lower.find(marker))
+        .min()
+        .map(|at| lower[..=at].rfind('<').unwrap_or(at));
+    match cut {
+        Some(at) => html[..at].trim_end().to_string(),
+        None => html.trim().to_string(),
+    }
+}
+
+/// Parse an RFC 5322 message with its MIME parts
+pub fn parse_message(raw: &[u8]) -> ParsedEmail {
+    let (head, body) = split_head(raw);
+    let headers = parse_headers(head);
+    let mut email = ParsedEmail::default();
+    walk_part(&headers, body, 0, &mut email);
+
+    email.message_id = header(&headers, "message-id")
+        .map(|id| id.trim().trim_start_matches('<').trim_end_matches('>'))
+        .filter(|id| !id.is_empty())
+        .map(|id| truncate(id, 500));
+    email.from = header(&headers, "from").and_then(|from| parse_address(&decode_words(from)));
+    email.subject = header(&headers, "subject")
+        .map(|subject| decode_words(subject).trim().to_string())
+        .unwrap_or_default();
+    email.headers = headers;
+    email
+}
+
+/// Collect the text, HTML and attachments of a part and the parts inside it
+fn walk_part(headers: &[(String, String)], body: &[u8], depth: usize, email: &mut ParsedEmail) {
+    let (mime, params) =
+        parse_header_value(header(headers, "content-type").unwrap_or("text/plain"));
+    if mime.starts_with("multipart/") && depth < MAX_MIME_DEPTH {
+        if let Some(boundary) = param(&params, "boundary") {
+            for part in split_multipart(body, &boundary) {
+                let (head, body) = split_head(part);
+                walk_part(&parse_headers(head), body, depth + 1, email);
+            }
+            return;
+        }
+    }
+
+    let (disposition, disposition_params) =
+        parse_header_value(header(headers, "content-disposition").unwrap_or(""));
+    let filename = param(&disposition_params, "filename")
+        .or_else(|| param(&params, "name"))
+        .map(|name| decode_words(&name))
+        .map(|name| sanitize_filename(&name))
+        .filter(|name| !name.is_empty());
+    let data = decode_transfer(body, header(headers, "content-transfer-encoding"));
+    let is_body = disposition != "attachment"
+        && filename.is_none()
+        && (mime == "text/plain" || mime == "text/html");
+
+    if is_body {
+        let text = decode_charset(&data, param(&params, "charset").as_deref());
+        let slot = if mime == "text/html" {
+            &mut email.html
+        } else {
+            &mut email.text
+        };
+        match slot {
+            Some(existing) => {
+                existing.push('\n');
+                existing.push_str(&text);
+            }
+            None => *slot = Some(text),
+        }
+        return;
+    }
+    if data.is_empty() {
+        return;
+    }
+    let content_id = header(headers, "content-id")
+        .map(|id| {
+            id.trim()
+                .trim_start_matches('<')
+                .trim_end_matches('>')
+                .to_string()
+        })
+        .filter(|id| !id.is_empty());
+    let filename = filename.unwrap_or_else(|| {
+        let ext = match mime.as_str() {
+            "image/jpeg" => "jpg",
+            "image/png" => "png",
+            "image/gif" => "gif",
+            "image/webp" => "webp",
+            "message/rfc822" => "eml",
+            "text/plain" => "txt",
+            "text/html" => "html",
+            _ => "bin",
+        };
+        format!("attachment-{}.{}", email.attachments.len() + 1, ext)
+    });
+    email.attachments.push(EmailAttachment {
+        filename,
+        content_type: mime,
+        content_id,
+        data: Bytes::from(data),
+    });
+}
+
+/// Headers and body of a message or part
+fn split_head(raw: &[u8]) -> (&[u8], &[u8]) {
+    if raw.starts_with(b"\r\n") {
+        return (&[], &raw[2..]);
+    }
+    if raw.starts_with(b"\n") {
+        return (&[], &raw[1..]);
+    }
+    let crlf = raw
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .map(|i| (i, i + 4));
+    let lf = raw
+        .windows(2)
+        .position(|w| w == b"\n\n")
+        .map(|i| (i, i + 2));
+    match (crlf, lf) {
+        (Some(a), Some(b)) => {
+            let (end, start) = if a.0 < b.0 { a } else { b };
+            (&raw[..end], &raw[start..])
+        }
+        (Some((end, start)), None) | (None, Some((end, start))) => (&raw[..end], &raw[start..]),
+        (None, None) => (raw, &[]),
+    }
+}
+
+/// Header fields with folded lines joined
+fn parse_headers(head: &[u8]) -> Vec<(String, String)> {
+    let head = String::from_utf8_lossy(head);
+    let mut headers: Vec<(String, String)> = Vec::new();
+    for line in head.split('\n') {
+        let line = line.trim_end_matches('\r');
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = headers.last_mut() {
+                value.push_str(line);
+            }
+            continue;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_lowercase(), value.trim().to_string()));
+        }
+    }
+    headers
+}
+
+fn header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+/// A structured header value: the lowercased main value and its
+/// `; key=value` parameters
+fn parse_header_value(value: &str) -> (String, Vec<(String, String)>) {
+    let mut pieces = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut escaped = false;
+    for c in value.chars() {
+        if escaped {
+            current.push(c);
+            escaped = false;
+        } else if quoted && c == '\\' {
+            escaped = true;
+        } else if c == '"' {
+            quoted = !quoted;
+        } else if c == ';' && !quoted {
+            pieces.push(std::mem::take(&mut current));
+        } else {
+            current.push(c);
+        }
+    }
+    pieces.push(current);
+
+    let main = pieces
+        .first()
+        .map(|v| v.trim().to_lowercase())
+        .unwrap_or_default();
+    let params = pieces
+        .iter()
+        .skip(1)
+        .filter_map(|piece| piece.split_once('='))
+        .map(|(key, value)| (key.trim().to_lowercase(), value.trim().to_string()))
+        .collect();
+    (main, params)
+}
+
+/// A parameter, including the RFC 2231 `key*=charset''percent-encoded` form
+fn param(params: &[(String, String)], name: &str) -> Option<String> {
+    if let Some((_, value)) = params.iter().find(|(key, _)| key == name) {
+        return Some(value.clone());
+    }
+    let extended = format!("{}*", name);
+    let (_, value) = params.iter().find(|(key, _)| *key == extended)?;
+    let mut parts = value.splitn(3, '\'');
+    let (charset, _language, encoded) = (parts.next()?, parts.next()?, parts.next()?);
+    let decoded = urlencoding::decode_binary(encoded.as_bytes());
+    Some(decode_charset(&decoded, Some(charset)))
+}
+
+/// Parts of a multipart body, without the preamble and epilogue
+fn split_multipart<'a>(body: &'a [u8], boundary: &str) -> Vec<&'a [u8]> {
+    let delimiter = format!("--{}", boundary);
+    let delimiter = delimiter.as_bytes();
+    let mut parts = Vec::new();
+    let mut start: Option<usize> = None;
+    let mut pos = 0;
+    while pos < body.len() {
+        let end = body[pos..]
+            .iter()
+            .position(|&b| b == b'\n')
+            .map(|i| pos + i + 1)
+            .unwrap_or(body.len());
+        let line = &body[pos..end];
+        if let Some(rest) = line.strip_prefix(delimiter) {
+            if let Some(start) = start {
+                parts.push(trim_line_end(&body[start..pos]));
+            }
+            if rest.starts_with(b"--") {
+                return parts;
+            }
+            start = Some(end);
+        }
+        pos = end;
+    }
+    if let Some(start) = start {
+        parts.push(&body[start..]);
+    }
+    parts
+}
+
+/// Drop the line break that belongs to the following boundary
+fn trim_line_end(part: &[u8]) -> &[u8] {
+    part.strip_suffix(b"\r\n")
+        .or_else(|| part.strip_suffix(b"\n"))
+        .unwrap_or(part)
+}
+
+fn decode_transfer(body: &[u8], encoding: Option<&str>) -> Vec<u8> {
+    match encoding.map(|e| e.trim().to_lowercase()).as_deref() {
+        Some("base64") => {
+            let compact: Vec<u8> = body
+                .iter()
+                .copied()
+                .filter(|b| !b.is_ascii_whitespace())
+                .collect();
+            LENIENT_BASE64
+                .decode(compact)
+                .unwrap_or_else(|_| body.to_vec())
+        }
+        Some("quoted-printable") => {
+            quoted_printable::decode(body, quoted_printable::ParseMode::Robust)
+                .unwrap_or_else(|_| body.to_vec())
+        }
+        _ => body.to_vec(),
+    }
+}
+
+fn decode_charset(bytes: &[u8], charset: Option<&str>) -> String {
+    let encoding = charset
+        .and_then(|label| Encoding::for_label(label.trim().as_bytes()))
+        .unwrap_or(UTF_8);
+    encoding.decode(bytes).0.into_owned()
+}
+
+/// Decode RFC 2047 encoded words (`=?charset?B|Q?text?=`); whitespace
+/// between two encoded words is dropped
+pub fn decode_words(value: &str) -> String {
+    let mut out = String::new();
+    let mut rest = value;
+    let mut after_word = false;
+    while let Some(start) = rest.find("=?") {
+        let (before, candidate) = rest.split_at(start);
+        match decode_word(candidate) {
+            Some((decoded, len)) => {
+                if !(after_word && before.trim().is_empty()) {
+                    out.push_str(before);
+                }
+                out.push_str(&decoded);
+                rest = &candidate[len..];
+                after_word = true;
+            }
+            None => {
+                out.push_str(before);
+                out.push_str("=?");
+                rest = &candidate[2..];
+                after_word = false;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// One encoded word at the start of `text` and its length
+fn decode_word(text: &str) -> Option<(String, usize)> {
+    let inner = text.strip_prefix("=?")?;
+    let (label, rest) = inner.split_once('?')?;
+    let (encoding, rest) = rest.split_once('?')?;
+    let end = rest.find("?=")?;
+    let encoded = &rest[..end];
+    if label.is_empty() || encoded.contains(char::is_whitespace) {
+        return None;
+    }
+    let bytes = match encoding {
+        "B" | "b" => LENIENT_BASE64.decode(encoded).ok()?,
+        "Q" | "q" => {
+            let raw = encoded.as_bytes();
+            let mut bytes = Vec::with_capacity(raw.len());
+            let mut i = 0;
+            while i < raw.len() {
+                match raw[i] {
+                    b'_' => bytes.push(b' '),
+                    b'=' => {
+                        let hex = std::str::from_utf8(raw.get(i + 1..i + 3)?).ok()?;
+                        bytes.push(u8::from_str_radix(hex, 16).ok()?);
+                        i += 2;
+                    }
+                    b => bytes.push(b),
+                }
+                i += 1;
+            }
+            bytes
+        }
+        _ => return None,
+    };
+    // RFC 2231 allows a language after the charset: `utf-8*en`
+    let charset = label.split('*').next().unwrap_or(label);
+    let len = "=?".len() + label.len() + 1 + encoding.len() + 1 + end + "?=".len();
+    Some((decode_charset(&bytes, Some(charset)), len))
+}
+
+/// The address of a `From` header, lowercased
+fn parse_address(value: &str) -> Option<String> {
+    let address = match (value.rfind('<'), value.rfind('>')) {
+        (Some(start), Some(end)) if start < end => &value[start + 1..end],
+        _ => value
+            .split_whitespace()
+            .find(|token| token.contains('@'))?
+            .trim_matches(|c| matches!(c, '"' | '(' | ')' | ',')),
+    };
+    let address = address.trim().to_lowercase();
+    (address.contains('@') && !address.contains(char::is_whitespace)).then_some(address)
+}
+
+/// Keep the last path segment of an attachment name
+fn sanitize_filename(name: &str) -> String {
+    let name = name.rsplit(['/', '\\']).next().unwrap_or(name);
+    let name: String = name.chars().filter(|c| !c.is_control()).collect();
+    truncate(name.trim(), 200)
+}
+
+/// A byte stream an IMAP session runs over
+trait ImapStream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> ImapStream for T {}
+
+/// Response of one IMAP command
+#[derive(Debug, Default)]
+struct ImapResponse {
+    lines: Vec<String>,
+    literals: Vec<Vec<u8>>,
+}
+
+/// The few IMAP4rev1 commands needed to fetch and flag unseen messages
+struct ImapSession {
+    stream: BufStream<Box<dyn ImapStream>>,
+    tag: u32,
+}
+
+impl ImapSession {
+    async fn connect(host: &str, port: u16, tls: bool) -> Result<Self> {
+        let tcp = tokio::time::timeout(IMAP_TIMEOUT, TcpStream::connect((host, port)))
+            .await
+            .map_err(|_| Error::internal("IMAP connection timed out"))?
+            .map_err(|e| Error::internal(format!("IMAP connection failed: {}", e)))?;
+        let stream: Box<dyn ImapStream> = if tls {
+            let connector = tokio_native_tls::native_tls::TlsConnector::new()
+                .map_err(|e| Error::internal(format!("TLS setup failed: {}", e)))?;
+            let stream = tokio::time::timeout(
+                IMAP_TIMEOUT,
+                tokio_native_tls::TlsConnector::from(connector).connect(host, tcp),
+            )
+            .await
+            .map_err(|_| Error::internal("IMAP TLS handshake timed out"))?
+            .map_err(|e| Error::internal(format!("IMAP TLS handshake failed: {}", e)))?;
+            Box::new(stream)
+        } else {
+            Box::new(tcp)
+        };
+        Self::start(stream).await
+    }
+
+    /// Read the server greeting
+    async fn start(stream: Box<dyn ImapStream>) -> Result<Self> {
+        let mut session = Self {
+            stream: BufStream::new(stream),
+            tag: 0,
+        };
+        let greeting = session.read_line().await?;
+        if !greeting.starts_with("* OK") {
+            return Err(Error::internal(format!(
+                "Unexpected IMAP greeting: {}",
+                greeting.trim_end()
+            )));
+        }
+        Ok(session)
+    }
+
+    async fn login(&mut self, username: &str, password: &str) -> Result<()> {
+        self.command(&format!("LOGIN {} {}", quote(username), quote(password)))
+            .await
+            .map(|_| ())
+    }
+
+    async fn select(&mut self, folder: &str) -> Result<()> {
+        self.command(&format!("SELECT {}", quote(folder)))
+            .await
+            .map(|_| ())
+    }
+
+    async fn search_unseen(&mut self) -> Result<Vec<u32>> {
+        let response = self.command("UID SEARCH UNSEEN").await?;
+        Ok(response
+            .lines
+            .iter()
+            .filter_map(|line| line.strip_prefix("* SEARCH"))
+            .flat_map(|ids| ids.split_whitespace())
+            .filter_map(|id| id.parse().ok())
+            .collect())
+    }
+
+    /// The raw message with a UID, leaving it unseen
+    async fn fetch(&mut self, uid: u32) -> Result<Vec<u8>> {
+        self.command(&format!("UID FETCH {} BODY.PEEK[]", uid))
+            .await?
+            .literals
+            .into_iter()
+            .next()
+            .ok_or_else(|| Error::internal(format!("IMAP message {} has no body", uid)))
+    }
+
+    async fn mark_seen(&mut self, uid: u32) -> Result<()> {
+        self.command(&format!("UID STORE {} +FLAGS.SILENT (\\Seen)", uid))
+            .await
+            .map(|_| ())
+    }
+
+    async fn logout(&mut self) {
+        let _ = self.command("LOGOUT").await;
+    }
+
+    /// Send a command and read its response up to the tagged status
+    async fn command(&mut self, command: &str) -> Result<ImapResponse> {
+        self.tag += 1;
+        let tag = format!("A{:04}", self.tag);
+        let name = command.split(' ').next().unwrap_or(command);
+        let write = async {
+            self.stream
+                .write_all(format!("{} {}\r\n", tag, command).as_bytes())
+                .await?;
+            self.stream.flush().await
+        };
+        tokio::time::timeout(IMAP_TIMEOUT, write)
+            .await
+            .map_err(|_| Error::internal("IMAP server timed out"))?
+            .map_err(|e| Error::internal(format!("IMAP write failed: {}", e)))?;
+
+        let mut response = ImapResponse::default();
+        loop {
+            let line = self.read_line().await?;
+            if let Some(size) = literal_size(&line) {
+                if size > MAX_MESSAGE_SIZE * 2 {
+                    return Err(Error::internal("IMAP message is too large"));
+                }
+                let mut literal = vec![0; size];
+                tokio::time::timeout(IMAP_TIMEOUT, self.stream.read_exact(&mut literal))
+                    .await
+                    .map_err(|_| Error::internal("IMAP server timed out"))?
+                    .map_err(|e| Error::internal(format!("IMAP read failed: {}", e)))?;
+                response.literals.push(literal);
+                response.lines.push(line);
+                continue;
+            }
+            if let Some(status) = line
+                .strip_prefix(tag.as_str())
+                .and_then(|rest| rest.strip_prefix(' '))
+            {
+                if status.starts_with("OK") {
+                    return Ok(response);
+                }
+                return Err(Error::internal(format!(
+                    "IMAP {} failed: {}",
+                    name,
+                    status.trim_end()
+                )));
+            }
+            response.lines.push(line);
+        }
+    }
+
+    async fn read_line(&mut self) -> Result<String> {
+        let mut line = Vec::new();
+        let mut limited = (&mut self.stream).take(MAX_IMAP_LINE);
+        let count = tokio::time::timeout(IMAP_TIMEOUT, limited.read_until(b'\n', &mut line))
+            .await
+            .map_err(|_| Error::internal("IMAP server timed out"))?
+            .map_err(|e| Error::internal(format!("IMAP read failed: {}", e)))?;
+        if count == 0 {
+            return Err(Error::internal("IMAP server closed the connection"));
+        }
+        Ok(String::from_utf8_lossy(&line).into_owned())
+    }
+}
+
+/// Size of the literal (`{123}`) a response line ends with
+fn literal_size(line: &str) -> Option<usize> {
+    let line = line.trim_end_matches(['\r', '\n']);
+    let inner = line.strip_suffix('}')?;
+    let start = inner.rfind('{')?;
+    inner[start + 1..].parse().ok()
+}
+
+/// An IMAP quoted string
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Periodic poll of IMAP mailboxes
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PollInboundEmailJob {}
+
+impl JobPayload for PollInboundEmailJob {
+    fn job_type() -> &'static str {
+        "poll_inbound_email"
+    }
+
+    // Unseen messages are fetched again by the next run
+    fn max_attempts() -> u32 {
+        1
+    }
+}
+
+/// Handler for [`PollInboundEmailJob`]
+#[derive(Clone)]
+pub struct PollInboundEmailHandler {
+    inbound_email: Arc<InboundEmailService>,
+}
+
+impl PollInboundEmailHandler {
+    pub fn new(inbound_email: Arc<InboundEmailService>) -> Self {
+        Self { inbound_email }
+    }
+}
+
+#[async_trait]
+impl JobHandler for PollInboundEmailHandler {
+    type Payload = PollInboundEmailJob;
+
+    async fn handle(&self, _payload: Self::Payload) -> Result<()> {
+        let report = self.inbound_email.poll_all().await?;
+        if report.received > 0 || report.failed > 0 {
+            tracing::info!(
+                mailboxes = report.mailboxes,
+                received = report.received,
+                failed = report.failed,
+                "Inbound mailboxes polled"
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MULTIPART: &str = "From: =?UTF-8?Q?J=C3=BCrgen?= <Juergen@Example.com>\r\n\
+Subject: =?UTF-8?B?SGVsbG8g?= =?UTF-8?B?V8O2cmxk?=\r\n\
+Message-ID: <abc@mail.example.com>\r\n\
+Content-Type: multipart/mixed;\r\n boundary=\"outer\"\r\n\
+\r\n\
+preamble\r\n\
+--outer\r\n\
+Content-Type: multipart/alternative; boundary=inner\r\n\
+\r\n\
+--inner\r\n\
+Content-Type: text/plain; charset=iso-8859-1\r\n\
+Content-Transfer-Encoding: quoted-printable\r\n\
+\r\n\
+Gr=FC=DFe\r\n\
+--inner\r\n\
+Content-Type: text/html; charset=utf-8\r\n\
+\r\n\
+<p>Grüße <img src=\"cid:logo@x\"></p>\r\n\
+--inner--\r\n\
+--outer\r\n\
+Content-Type: image/png; name=\"logo.png\"\r\n\
+Content-ID: <logo@x>\r\n\
+Content-Transfer-Encoding: base64\r\n\
+\r\n\
+iVBORw0KGgo=\r\n\
+--outer\r\n\
+Content-Type: application/pdf\r\n\
+Content-Disposition: attachment; filename*=utf-8''r%C3%A9sum%C3%A9.pdf\r\n\
+Content-Transfer-Encoding: base64\r\n\
+\r\n\
+JVBERi0=\r\n\
+--outer--\r\n";
+
+    #[test]
+    fn test_parse_multipart_message() {
+        let email = parse_message(MULTIPART.as_bytes());
+        assert_eq!(email.from.as_deref(), Some("juergen@example.com"));
+        assert_eq!(email.subject, "Hello Wörld");
+        assert_eq!(email.message_id.as_deref(), Some("abc@mail.example.com"));
+        assert_eq!(email.text.as_deref(), Some("Grüße"));
+        assert_eq!(
+            email.html.as_deref(),
+            Some("<p>Grüße <img src=\"cid:logo@x\"></p>")
+        );
+
+        assert_eq!(email.attachments.len(), 2);
+        let logo = &email.attachments[0];
+        assert_eq!(logo.filename, "logo.png");
+        assert_eq!(logo.content_id.as_deref(), Some("logo@x"));
+        assert_eq!(&logo.data[..4], b"\x89PNG");
+        let pdf = &email.attachments[1];
+        assert_eq!(pdf.filename, "résumé.pdf");
+        assert_eq!(pdf.content_type, "application/pdf");
+        assert_eq!(&pdf.data[..], b"%PDF-");
+    }
+
+    #[test]
+    fn test_parse_plain_message() {
+        let email = parse_message(b"From: editor@example.com\nSubject: Plain\n\nBody line\n");
+        assert_eq!(email.from.as_deref(), Some("editor@example.com"));
+        assert_eq!(email.message_id, None);
+        assert_eq!(email.text.as_deref(), Some("Body line\n"));
+        assert!(email.attachments.is_empty());
+    }
+
+    #[test]
+    fn test_strip_signature() {
+        let text = "First paragraph.\n\nSecond one.\n\n-- \nJane Doe\nEditor";
+        assert_eq!(strip_signature(text), "First paragraph.\n\nSecond one.");
+
+        let reply = "New text\r\n\r\nOn Mon, 1 Jan 2024 at 10:00, Jane <jane@example.com>\r\nwrote:\r\n> old";
+        assert_eq!(strip_signature(reply), "New text");
+
+        assert_eq!(strip_signature("Hi\n\nSent from my iPhone"), "Hi");
+        assert_eq!(strip_signature("Hi\n> quoted\n>"), "Hi");
+    }
+
+    #[test]
+    fn test_strip_html_signature() {
+        let html = "<html><head><style>p{}</style></head><body><p>Post</p>\
+<div class=\"gmail_signature\" dir=\"ltr\">Jane</div></body></html>";
+        assert_eq!(strip_html_signature(html_body(html)), "<p>Post</p>");
+        assert_eq!(
+            strip_html_signature("<p>Post</p><blockquote type=\"cite\">old</blockquote>"),
+            "<p>Post</p>"
+        );
+        assert_eq!(strip_html_signature("<p>Post</p>"), "<p>Post</p>");
+    }
+
+    #[test]
+    fn test_text_to_html_escapes() {
+        assert_eq!(
+            text_to_html("a < b\nline two\n\n\nnext"),
+            "<p>a &lt; b<br>\nline two</p>\n<p>next</p>"
+        );
+    }
+
+    #[test]
+    fn test_mailgun_signature() {
+        let key = hmac::Key::new(hmac::HMAC_SHA256, b"key-secret");
+        let tag = hmac::sign(&key, b"1700000000token123");
+        let signature: String = tag.as_ref().iter().map(|b| format!("{:02x}", b)).collect();
+
+        assert!(verify_mailgun_signature(
+            "key-secret",
+            "1700000000",
+            "token123",
+            &signature,
+            1_700_000_060
+        )
+        .is_ok());
+        assert!(verify_mailgun_signature(
+            "other",
+            "1700000000",
+            "token123",
+            &signature,
+            1_700_000_060
+        )
+        .is_err());
+        assert!(verify_mailgun_signature(
+            "key-secret",
+            "1700000000",
+            "token123",
+            &signature,
+            1_700_090_000
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_authentication_results() {
+        let forged = parse_message(
+            b"Authentication-Results: mx.example.com; spf=fail; dmarc=fail (p=REJECT)\n\
+From: ceo@example.com\n\nhi",
+        );
+        assert!(!forged.authentication_passed());
+        let genuine = parse_message(
+            b"Authentication-Results: mx.example.com; dkim=pass; dmarc=pass\n\
+From: ceo@example.com\n\nhi",
+        );
+        assert!(genuine.authentication_passed());
+        // Mail without results, or with the sender's own below the server's
+        assert!(!parse_message(b"From: ceo@example.com\n\nhi").authentication_passed());
+        let smuggled = parse_message(
+            b"Authentication-Results: mx.example.com; dmarc=none\n\
+Authentication-Results: evil.example; dmarc=pass\n\
+From: ceo@example.com\n\nhi",
+        );
+        assert!(!smuggled.authentication_passed());
+    }
+
+    #[test]
+    fn test_sns_string_to_sign() {
+        let notification = serde_json::json!({
+            "Type": "Notification",
+            "MessageId": "id-1",
+            "TopicArn": "arn:aws:sns:us-east-1:123456789012:mail",
+            "Message": "{}",
+            "Timestamp": "2026-01-01T00:00:00.000Z",
+            "SignatureVersion": "2",
+        });
+        assert_eq!(
+            sns_string_to_sign(&notification).unwrap(),
+            "Message\n{}\nMessageId\nid-1\nTimestamp\n2026-01-01T00:00:00.000Z\n\
+TopicArn\narn:aws:sns:us-east-1:123456789012:mail\nType\nNotification\n"
+        );
+        // Confirmations must carry what they are signed over
+        let confirmation = serde_json::json!({ "Type": "SubscriptionConfirmation" });
+        assert!(sns_string_to_sign(&confirmation).is_none());
+    }
+
+    #[test]
+    fn test_sns_certificate_url() {
+        assert!(sns_certificate_url(
+            "https://sns.us-east-1.amazonaws.com/SimpleNotificationService-abc.pem"
+        )
+        .is_ok());
+        for url in [
+            "http://sns.us-east-1.amazonaws.com/cert.pem",
+            "https://sns.us-east-1.amazonaws.com.evil.example/cert.pem",
+            "https://evil.amazonaws.com/cert.pem",
+            "https://sns.evil.example.amazonaws.com/cert.pem",
+            "https://sns.us-east-1.amazonaws.com:8443/cert.pem",
+            "https://sns.us-east-1.amazonaws.com/cert.txt",
+        ] {
+            assert!(sns_certificate_url(url).is_err(), "{}", url);
+        }
+    }
+
+    #[test]
+    fn test_allowed_senders() {
+        let senders = check_allowed_senders(vec![
+            " Writer@Example.com ".into(),
+            "@staff.example.org".into(),
+            "writer@example.com".into(),
+        ])
+        .unwrap();
+        assert_eq!(senders, vec!["writer@example.com", "@staff.example.org"]);
+        assert!(check_allowed_senders(vec!["not-an-address".into()]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_imap_session_fetches_unseen_messages() {
+        let (client, server) = tokio::io::duplex(4096);
+        let script = tokio::spawn(async move {
+            let mut server = BufStream::new(server);
+            server.write_all(b"* OK ready\r\n").await.unwrap();
+            server.flush().await.unwrap();
+            let mut replies = vec![
+                "A0001 OK logged in\r\n".to_string(),
+                "* 2 EXISTS\r\nA0002 OK selected\r\n".to_string(),
+                "* SEARCH 7 9\r\nA0003 OK\r\n".to_string(),
+                "* 1 FETCH (UID 7 BODY[] {11}\r\nSubject: x\n)\r\nA0004 OK\r\n".to_string(),
+            ]
+            .into_iter();
+            let mut commands = Vec::new();
+            loop {
+                let mut line = String::new();
+                if server.read_line(&mut line).await.unwrap() == 0 {
+                    break;
+                }
+                commands.push(line.trim_end().to_string());
+                let Some(reply) = replies.next() else { break };
+                server.write_all(reply.as_bytes()).await.unwrap();
+                server.flush().await.unwrap();
+            }
+            commands
+        });
+
+        let mut session = ImapSession::start(Box::new(client)).await.unwrap();
+        session.login("user", "pa\"ss").await.unwrap();
+        session.select("INBOX").await.unwrap();
+        assert_eq!(session.search_unseen().await.unwrap(), vec![7, 9]);
+        assert_eq!(session.fetch(7).await.unwrap(), b"Subject: x\n");
+        drop(session);
+
+        let commands = script.await.unwrap();
+        assert_eq!(commands[0], "A0001 LOGIN \"user\" \"pa\\\"ss\"");
+        assert_eq!(commands[3], "A0004 UID FETCH 7 BODY.PEEK[]");
+    }
+}
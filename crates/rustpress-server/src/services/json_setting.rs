@@ -0,0 +1,117 @@
+//! Typed JSON documents stored in the settings table.
+//!
+//! Services that keep their configuration as a single JSON value (robots,
+//! page cache, CAPTCHA, compliance, ...) declare a [`JsonSetting`] constant
+//! instead of hand-writing the same select/upsert pair.
+
+use rustpress_core::error::{Error, Result};
+use serde::{de::DeserializeOwned, Serialize};
+use sqlx::PgExecutor;
+use std::marker::PhantomData;
+
+/// A JSON-encoded value stored under one settings key
+pub struct JsonSetting<T> {
+    key: &'static str,
+    group: &'static str,
+    label: &'static str,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> JsonSetting<T> {
+    /// Declare a setting; `label` names it in error messages
+    pub const fn new(key: &'static str, group: &'static str, label: &'static str) -> Self {
+        Self {
+            key,
+            group,
+            label,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Settings key the value is stored under
+    pub fn key(&self) -> &'static str {
+        self.key
+    }
+}
+
+impl<T: Serialize + DeserializeOwned> JsonSetting<T> {
+    /// Load the stored value, if any
+    pub async fn load<'e>(&self, executor: impl PgExecutor<'e>) -> Result<Option<T>> {
+        let row: Option<(Option<String>,)> =
+            sqlx::query_as("SELECT value FROM settings WHERE key = $1")
+                .bind(self.key)
+                .fetch_optional(executor)
+                .await
+                .map_err(|e| {
+                    Error::database_with_source(format!("Failed to load {}", self.label), e)
+                })?;
+
+        row.and_then(|(value,)| value)
+            .map(|value| self.decode(&value))
+            .transpose()
+    }
+
+    /// Load the stored value, falling back to the type's default
+    pub async fn load_or_default<'e>(&self, executor: impl PgExecutor<'e>) -> Result<T>
+    where
+        T: Default,
+    {
+        Ok(self.load(executor).await?.unwrap_or_default())
+    }
+
+    /// Persist the value, replacing any previous one
+    pub async fn save<'e>(&self, executor: impl PgExecutor<'e>, value: &T) -> Result<()> {
+        let encoded = serde_json::to_string(value)
+            .map_err(|e| Error::internal(format!("Failed to encode {}: {}", self.label, e)))?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO settings (id, key, value, type, group_name, updated_at)
+            VALUES (gen_random_uuid(), $1, $2, 'json', $3, NOW())
+            ON CONFLICT (key) DO UPDATE SET value = $2, updated_at = NOW()
+            "#,
+        )
+        .bind(self.key)
+        .bind(encoded)
+        .bind(self.group)
+        .execute(executor)
+        .await
+        .map_err(|e| Error::database_with_source(format!("Failed to save {}", self.label), e))?;
+
+        Ok(())
+    }
+
+    /// Serialize read-modify-write cycles on this setting until the
+    /// surrounding transaction ends
+    pub async fn lock<'e>(&self, executor: impl PgExecutor<'e>) -> Result<()> {
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1))")
+            .bind(self.key)
+            .execute(executor)
+            .await
+            .map_err(|e| {
+                Error::database_with_source(format!("Failed to lock {}", self.label), e)
+            })?;
+        Ok(())
+    }
+
+    fn decode(&self, value: &str) -> Result<T> {
+        serde_json::from_str(value)
+            .map_err(|e| Error::internal(format!("Invalid {}: {}", self.label, e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NUMBERS: JsonSetting<Vec<u32>> = JsonSetting::new("numbers", "test", "number list");
+
+    #[test]
+    fn test_decode_reports_label() {
+        assert_eq!(NUMBERS.key(), "numbers");
+        assert_eq!(NUMBERS.decode("[1,2]").unwrap(), vec![1, 2]);
+
+        let err = NUMBERS.decode("{}").unwrap_err().to_string();
+        assert!(err.contains("Invalid number list"), "{}", err);
+    }
+}
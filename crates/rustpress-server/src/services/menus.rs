@@ -0,0 +1,1062 @@
+//! Navigation menus
+//!
+//! Menus hold nested items, each linking to a post, a term or a custom URL:
+//!
+//! - post and term links follow their object. They take its current path
+//!   and, unless the item sets its own title, its current title. Links to
+//!   unpublished posts, to terms without archives and to deleted objects
+//!   are left out of rendered menus
+//! - items are shown to everyone, to logged in or logged out visitors only,
+//!   or to some roles. Hiding an item hides the items nested under it
+//! - themes declare menu locations. A menu fills a location when it is
+//!   assigned to it for the theme, or else when its own location names it
+//!
+//! Public pages are cached for every visitor, so themes render menus the
+//! way anonymous visitors see them. Clients showing a signed-in viewer's
+//! menu fetch it from the API.
+
+use rustpress_core::error::{Error, Result};
+use rustpress_core::tenant::site_filter;
+use rustpress_database::repository::menus::{MenuItemRow, MenuRow, MenusRepository, SaveMenu};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use sqlx::PgPool;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use super::redirects::content_path;
+use super::render_service::{MenuData, MenuItemData};
+use super::theme_service::ThemeService;
+
+/// Items one menu may hold
+const MAX_ITEMS: usize = 500;
+
+/// Deepest nesting of items; top-level items are at depth 1
+const MAX_DEPTH: usize = 6;
+
+/// Longest menu slug
+const MAX_MENU_SLUG: usize = 200;
+
+/// Locations offered when the theme declares none
+const DEFAULT_LOCATIONS: &[(&str, &str)] = &[
+    ("primary", "Primary Navigation"),
+    ("secondary", "Secondary Navigation"),
+    ("footer", "Footer Menu"),
+    ("social", "Social Links"),
+    ("mobile", "Mobile Menu"),
+];
+
+/// Locations themes always get a menu for, empty when nothing fills them
+const PLACEHOLDER_LOCATIONS: &[&str] = &["primary", "footer", "social"];
+
+/// What a menu item links to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MenuItemKind {
+    /// A URL of its own
+    #[default]
+    Custom,
+    /// A post of any type
+    Post,
+    /// A term of any taxonomy
+    Term,
+}
+
+impl MenuItemKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Custom => "custom",
+            Self::Post => "post",
+            Self::Term => "term",
+        }
+    }
+
+    /// Unknown kinds of older items are custom links
+    fn parse(value: &str) -> Self {
+        match value {
+            "post" => Self::Post,
+            "term" => Self::Term,
+            _ => Self::Custom,
+        }
+    }
+}
+
+/// Who sees a menu item
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MenuVisibility {
+    #[default]
+    Everyone,
+    LoggedIn,
+    LoggedOut,
+    /// Only users with one of the item's roles
+    Roles,
+}
+
+impl MenuVisibility {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Everyone => "everyone",
+            Self::LoggedIn => "logged_in",
+            Self::LoggedOut => "logged_out",
+            Self::Roles => "roles",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        match value {
+            "logged_in" => Self::LoggedIn,
+            "logged_out" => Self::LoggedOut,
+            "roles" => Self::Roles,
+            _ => Self::Everyone,
+        }
+    }
+
+    /// Whether a viewer with some roles sees the item; `None` is an
+    /// anonymous visitor
+    pub fn allows(&self, roles: &[String], viewer: Option<&[String]>) -> bool {
+        match (self, viewer) {
+            (Self::Everyone, _) => true,
+            (Self::LoggedIn, viewer) => viewer.is_some(),
+            (Self::LoggedOut, viewer) => viewer.is_none(),
+            (Self::Roles, Some(viewer)) => viewer.iter().any(|r| roles.contains(r)),
+            (Self::Roles, None) => false,
+        }
+    }
+}
+
+/// A menu to create, or new settings for one. Leaving out the slug
+/// derives it from the name on create and keeps it on update.
+#[derive(Debug, Clone, Deserialize)]
+pub struct MenuInput {
+    pub name: String,
+    pub slug: Option<String>,
+    pub description: Option<String>,
+    /// Location the menu fills when the theme has no assignment for it
+    pub location: Option<String>,
+    /// Version the update is based on, when not sent as `If-Match`
+    pub version: Option<i64>,
+}
+
+impl MenuInput {
+    fn normalize(self, current_slug: Option<&str>) -> Result<SaveMenu> {
+        let name = self.name.trim().to_string();
+        if name.is_empty() || name.chars().count() > 255 {
+            return Err(Error::invalid_input(
+                "name",
+                "A menu needs a name of at most 255 characters",
+            ));
+        }
+        let slug = match (non_empty(self.slug), current_slug) {
+            (Some(slug), _) => menu_slug(&slug),
+            (None, Some(current)) => current.to_string(),
+            (None, None) => menu_slug(&name),
+        };
+        if slug.is_empty() {
+            return Err(Error::invalid_input(
+                "slug",
+                "The menu slug has no letters or digits",
+            ));
+        }
+        let location = non_empty(self.location).map(|l| l.to_lowercase());
+        if let Some(ref location) = location {
+            if !is_location(location) {
+                return Err(Error::invalid_input(
+                    "location",
+                    "Locations are lowercase letters, digits, '-' and '_'",
+                ));
+            }
+        }
+        Ok(SaveMenu {
+            name,
+            slug,
+            description: non_empty(self.description),
+            location,
+        })
+    }
+}
+
+/// An item of a menu, with the items nested under it
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct MenuItemInput {
+    /// Required for custom links; post and term links default to the
+    /// title of their object
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub kind: MenuItemKind,
+    /// Target of custom links
+    pub url: Option<String>,
+    /// Post or term the item links to
+    pub object_id: Option<Uuid>,
+    /// `_self` or `_blank`
+    pub target: Option<String>,
+    pub css_classes: Option<String>,
+    #[serde(default)]
+    pub visibility: MenuVisibility,
+    /// Roles that see the item when its visibility is `roles`
+    #[serde(default)]
+    pub roles: Vec<String>,
+    #[serde(default)]
+    pub children: Vec<MenuItemInput>,
+}
+
+/// The whole item tree of a menu, replacing the one it has
+#[derive(Debug, Clone, Deserialize)]
+pub struct MenuItemsInput {
+    pub items: Vec<MenuItemInput>,
+    /// Version the change is based on, when not sent as `If-Match`
+    pub version: Option<i64>,
+}
+
+/// A menu item as editors see it
+#[derive(Debug, Clone, Serialize)]
+pub struct MenuItem {
+    pub id: Uuid,
+    /// Title set on the item; empty when it follows its object
+    pub title: String,
+    pub kind: MenuItemKind,
+    pub url: Option<String>,
+    pub object_id: Option<Uuid>,
+    /// Where the item links to now; `None` when its object is gone or not
+    /// public, which leaves the item out of rendered menus
+    pub link: Option<String>,
+    /// Current title of the post or term linked to
+    pub object_title: Option<String>,
+    pub target: Option<String>,
+    pub css_classes: Option<String>,
+    pub visibility: MenuVisibility,
+    pub roles: Vec<String>,
+    pub children: Vec<MenuItem>,
+}
+
+/// A menu with its item tree
+#[derive(Debug, Clone, Serialize)]
+pub struct Menu {
+    #[serde(flatten)]
+    pub menu: MenuRow,
+    pub items: Vec<MenuItem>,
+}
+
+/// A menu location of a theme
+#[derive(Debug, Clone, Serialize)]
+pub struct MenuLocation {
+    pub slug: String,
+    pub name: String,
+    /// Menu filling the location
+    pub menu_id: Option<Uuid>,
+    /// Whether the menu is assigned for the theme rather than naming the
+    /// location itself
+    pub assigned: bool,
+}
+
+/// Current path and title of a post or term
+#[derive(Debug, Clone)]
+struct LinkTarget {
+    path: String,
+    title: String,
+}
+
+/// Navigation menu service
+pub struct MenuService {
+    pool: PgPool,
+    themes: Arc<ThemeService>,
+}
+
+impl MenuService {
+    pub fn new(pool: PgPool, themes: Arc<ThemeService>) -> Self {
+        Self { pool, themes }
+    }
+
+    fn repo(&self) -> MenusRepository {
+        let repo = MenusRepository::new(self.pool.clone());
+        match self.themes.site_id() {
+            Some(site_id) => repo.with_site(site_id),
+            None => repo,
+        }
+    }
+
+    pub async fn list(&self) -> Result<Vec<MenuRow>> {
+        self.repo().list().await
+    }
+
+    async fn row(&self, id: Uuid) -> Result<MenuRow> {
+        self.repo()
+            .find(id)
+            .await?
+            .ok_or_else(|| Error::not_found("Menu", id.to_string()))
+    }
+
+    /// A menu with all of its items, whoever they are shown to
+    pub async fn menu(&self, id: Uuid) -> Result<Menu> {
+        let menu = self.row(id).await?;
+        let rows = self.repo().items(&[id]).await?;
+        let targets = self.targets(&rows).await?;
+        let items = editor_tree((id, None), &children_of(&rows), &targets);
+        Ok(Menu { menu, items })
+    }
+
+    pub async fn create(&self, input: MenuInput) -> Result<MenuRow> {
+        self.repo().create(&input.normalize(None)?).await
+    }
+
+    pub async fn update(
+        &self,
+        id: Uuid,
+        input: MenuInput,
+        expected_version: Option<i64>,
+    ) -> Result<MenuRow> {
+        let current = self.row(id).await?;
+        let expected_version = expected_version.or(input.version);
+        let menu = input.normalize(Some(&current.slug))?;
+        self.repo().update(id, &menu, expected_version).await
+    }
+
+    pub async fn delete(&self, id: Uuid) -> Result<()> {
+        if self.repo().delete(id).await? {
+            Ok(())
+        } else {
+            Err(Error::not_found("Menu", id.to_string()))
+        }
+    }
+
+    /// Replace the item tree of a menu
+    pub async fn save_items(
+        &self,
+        id: Uuid,
+        input: MenuItemsInput,
+        expected_version: Option<i64>,
+    ) -> Result<Menu> {
+        self.row(id).await?;
+        let expected_version = expected_version.or(input.version);
+        let rows = flatten(id, input.items)?;
+        self.check_objects(&rows).await?;
+        self.repo()
+            .replace_items(id, &rows, expected_version)
+            .await?;
+        self.menu(id).await
+    }
+
+    /// The requested theme, or the active one
+    pub async fn theme_id(&self, requested: Option<String>) -> Result<String> {
+        match non_empty(requested) {
+            Some(theme_id) => Ok(theme_id),
+            None => self
+                .themes
+                .get_active_theme_id()
+                .await?
+                .ok_or_else(|| Error::not_found("Theme", "active")),
+        }
+    }
+
+    /// Menu locations a theme declares, with the menu filling each
+    pub async fn locations(&self, theme_id: &str) -> Result<Vec<MenuLocation>> {
+        let assigned = self.themes.menu_locations(theme_id).await?;
+        let menus = self.list().await?;
+        let placed = placements(&assigned, &menus);
+        Ok(self
+            .declared_locations(theme_id)
+            .await
+            .into_iter()
+            .map(|(slug, name)| MenuLocation {
+                menu_id: placed.get(&slug).copied(),
+                assigned: assigned.contains_key(&slug),
+                slug,
+                name,
+            })
+            .collect())
+    }
+
+    /// Assign a menu to a location of a theme, or clear the assignment
+    pub async fn assign(
+        &self,
+        theme_id: &str,
+        location: &str,
+        menu_id: Option<Uuid>,
+    ) -> Result<()> {
+        let declared = self.declared_locations(theme_id).await;
+        if !declared.iter().any(|(slug, _)| slug == location) {
+            return Err(Error::invalid_input(
+                "location",
+                format!("The theme has no menu location '{}'", location),
+            ));
+        }
+        if let Some(menu_id) = menu_id {
+            self.row(menu_id).await?;
+        }
+        self.themes.assign_menu(theme_id, location, menu_id).await
+    }
+
+    /// Menu filling a location of a theme, as a viewer sees it
+    pub async fn location_menu(
+        &self,
+        theme_id: &str,
+        location: &str,
+        viewer: Option<&[String]>,
+    ) -> Result<Option<MenuData>> {
+        let assigned = self.themes.menu_locations(theme_id).await?;
+        let menus = self.list().await?;
+        let Some(menu_id) = placements(&assigned, &menus).get(location).copied() else {
+            return Ok(None);
+        };
+        let Some(menu) = menus.into_iter().find(|m| m.id == menu_id) else {
+            return Ok(None);
+        };
+        let rows = self.repo().items(&[menu_id]).await?;
+        let targets = self.targets(&rows).await?;
+        Ok(Some(MenuData {
+            name: menu.name,
+            slug: menu.slug,
+            items: render_tree((menu_id, None), &children_of(&rows), &targets, viewer),
+        }))
+    }
+
+    /// Menus for a theme's templates, keyed by the location they fill and
+    /// also by their slug. Locations take precedence over slugs.
+    pub async fn theme_menus(
+        &self,
+        theme_id: &str,
+        viewer: Option<&[String]>,
+    ) -> Result<HashMap<String, MenuData>> {
+        let assigned = self.themes.menu_locations(theme_id).await?;
+        let menus = self.list().await?;
+        let placed = placements(&assigned, &menus);
+
+        let ids: Vec<Uuid> = menus.iter().map(|m| m.id).collect();
+        let rows = self.repo().items(&ids).await?;
+        let targets = self.targets(&rows).await?;
+        let children = children_of(&rows);
+
+        let mut by_id = HashMap::new();
+        for menu in &menus {
+            let data = MenuData {
+                name: menu.name.clone(),
+                slug: menu.slug.clone(),
+                items: render_tree((menu.id, None), &children, &targets, viewer),
+            };
+            by_id.insert(menu.id, data);
+        }
+
+        let mut result: HashMap<String, MenuData> = menus
+            .iter()
+            .map(|m| (m.slug.clone(), by_id[&m.id].clone()))
+            .collect();
+        for (location, menu_id) in placed {
+            if let Some(data) = by_id.get(&menu_id) {
+                result.insert(location, data.clone());
+            }
+        }
+        for location in PLACEHOLDER_LOCATIONS {
+            result
+                .entry(location.to_string())
+                .or_insert_with(|| MenuData {
+                    name: format!("{} Menu", location),
+                    slug: location.to_string(),
+                    items: vec![],
+                });
+        }
+        Ok(result)
+    }
+
+    /// Locations the theme declares in its `menu_locations`, or the default
+    /// ones when it declares none
+    async fn declared_locations(&self, theme_id: &str) -> Vec<(String, String)> {
+        let declared: Vec<(String, String)> = match self.themes.get_theme(theme_id).await {
+            Ok(Some(theme)) => theme
+                .menu_locations
+                .as_object()
+                .map(|locations| {
+                    locations
+                        .iter()
+                        .map(|(slug, name)| {
+                            let name = name.as_str().unwrap_or(slug).to_string();
+                            (slug.clone(), name)
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+            Ok(None) => Vec::new(),
+            Err(e) => {
+                tracing::warn!(theme_id, "Failed to load theme menu locations: {}", e);
+                Vec::new()
+            }
+        };
+        if declared.is_empty() {
+            DEFAULT_LOCATIONS
+                .iter()
+                .map(|(slug, name)| (slug.to_string(), name.to_string()))
+                .collect()
+        } else {
+            declared
+        }
+    }
+
+    /// Posts and terms linked from items must exist; drafts may be linked
+    /// before they are published
+    async fn check_objects(&self, rows: &[MenuItemRow]) -> Result<()> {
+        for kind in [MenuItemKind::Post, MenuItemKind::Term] {
+            let ids: Vec<Uuid> = rows
+                .iter()
+                .filter(|r| MenuItemKind::parse(&r.object_type) == kind)
+                .filter_map(|r| r.object_id)
+                .collect::<HashSet<_>>()
+                .into_iter()
+                .collect();
+            if ids.is_empty() {
+                continue;
+            }
+            let query = match kind {
+                MenuItemKind::Post => format!(
+                    "SELECT id FROM posts WHERE id = ANY($1) AND {} AND deleted_at IS NULL",
+                    site_filter("site_id")
+                ),
+                _ => "SELECT id FROM terms WHERE id = ANY($1)".to_string(),
+            };
+            let found: HashSet<Uuid> = sqlx::query_scalar(&query)
+                .bind(&ids)
+                .fetch_all(&self.pool)
+                .await
+                .map_err(|e| Error::database_with_source("Failed to check menu links", e))?
+                .into_iter()
+                .collect();
+            if let Some(missing) = ids.iter().find(|id| !found.contains(id)) {
+                let noun = if kind == MenuItemKind::Post {
+                    "Post"
+                } else {
+                    "Term"
+                };
+                return Err(Error::invalid_input(
+                    "items",
+                    format!("{} {} does not exist", noun, missing),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Current path and title of the published posts and public terms
+    /// items link to, by object id
+    async fn targets(&self, rows: &[MenuItemRow]) -> Result<HashMap<Uuid, LinkTarget>> {
+        let ids_of = |kind: MenuItemKind| -> Vec<Uuid> {
+            rows.iter()
+                .filter(|r| MenuItemKind::parse(&r.object_type) == kind)
+                .filter_map(|r| r.object_id)
+                .collect()
+        };
+        let mut targets = HashMap::new();
+
+        let post_ids = ids_of(MenuItemKind::Post);
+        if !post_ids.is_empty() {
+            let posts: Vec<(Uuid, String, String, String)> = sqlx::query_as(&format!(
+                r#"
+                SELECT id, title, slug, post_type
+                FROM posts
+                WHERE id = ANY($1) AND {} AND status = 'published' AND deleted_at IS NULL
+                "#,
+                site_filter("site_id")
+            ))
+            .bind(&post_ids)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to load linked posts", e))?;
+            for (id, title, slug, post_type) in posts {
+                let path = content_path(&post_type, &slug);
+                targets.insert(id, LinkTarget { path, title });
+            }
+        }
+
+        let term_ids = ids_of(MenuItemKind::Term);
+        if !term_ids.is_empty() {
+            let terms: Vec<(Uuid, String, String, String)> = sqlx::query_as(
+                r#"
+                SELECT t.id, t.name, t.slug, COALESCE(tx.rewrite_slug, tx.slug)
+                FROM terms t
+                JOIN taxonomies tx ON tx.id = t.taxonomy_id
+                WHERE t.id = ANY($1) AND tx.public
+                "#,
+            )
+            .bind(&term_ids)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to load linked terms", e))?;
+            for (id, name, slug, route) in terms {
+                let path = format!("/{}/{}", route, slug);
+                targets.insert(id, LinkTarget { path, title: name });
+            }
+        }
+
+        Ok(targets)
+    }
+}
+
+/// Check an item tree and flatten it parents first, giving every item a
+/// new id and its position among its siblings
+fn flatten(menu_id: Uuid, items: Vec<MenuItemInput>) -> Result<Vec<MenuItemRow>> {
+    let mut rows = Vec::new();
+    flatten_into(menu_id, None, items, 1, &mut rows)?;
+    Ok(rows)
+}
+
+fn flatten_into(
+    menu_id: Uuid,
+    parent_id: Option<Uuid>,
+    items: Vec<MenuItemInput>,
+    depth: usize,
+    rows: &mut Vec<MenuItemRow>,
+) -> Result<()> {
+    if !items.is_empty() && depth > MAX_DEPTH {
+        return Err(Error::invalid_input(
+            "items",
+            format!("Menus nest at most {} levels deep", MAX_DEPTH),
+        ));
+    }
+    for (position, mut item) in items.into_iter().enumerate() {
+        if rows.len() >= MAX_ITEMS {
+            return Err(Error::invalid_input(
+                "items",
+                format!("A menu holds at most {} items", MAX_ITEMS),
+            ));
+        }
+        let id = Uuid::now_v7();
+        let children = std::mem::take(&mut item.children);
+        rows.push(item_row(menu_id, id, parent_id, position as i32, item)?);
+        flatten_into(menu_id, Some(id), children, depth + 1, rows)?;
+    }
+    Ok(())
+}
+
+fn item_row(
+    menu_id: Uuid,
+    id: Uuid,
+    parent_id: Option<Uuid>,
+    menu_order: i32,
+    item: MenuItemInput,
+) -> Result<MenuItemRow> {
+    let title = item.title.trim().to_string();
+    if title.chars().count() > 255 {
+        return Err(Error::invalid_input(
+            "title",
+            "Menu item titles are at most 255 characters",
+        ));
+    }
+
+    let (url, object_id) = match item.kind {
+        MenuItemKind::Custom => {
+            let url = non_empty(item.url)
+                .ok_or_else(|| Error::invalid_input("url", "Custom links need a URL"))?;
+            check_url(&url)?;
+            if title.is_empty() {
+                return Err(Error::invalid_input("title", "Custom links need a title"));
+            }
+            (Some(url), None)
+        }
+        MenuItemKind::Post | MenuItemKind::Term => {
+            let object_id = item.object_id.ok_or_else(|| {
+                Error::invalid_input(
+                    "object_id",
+                    format!("{} links need an object_id", item.kind.as_str()),
+                )
+            })?;
+            (None, Some(object_id))
+        }
+    };
+
+    let target = non_empty(item.target);
+    if let Some(ref target) = target {
+        if target != "_self" && target != "_blank" {
+            return Err(Error::invalid_input(
+                "target",
+                "The target is either _self or _blank",
+            ));
+        }
+    }
+
+    let classes: Vec<&str> = item
+        .css_classes
+        .as_deref()
+        .unwrap_or_default()
+        .split_whitespace()
+        .collect();
+    if classes.iter().any(|c| !is_css_class(c)) {
+        return Err(Error::invalid_input(
+            "css_classes",
+            "Classes are letters, digits, '-' and '_'",
+        ));
+    }
+    let css_classes = Some(classes.join(" ")).filter(|c| !c.is_empty());
+    if css_classes.as_ref().is_some_and(|c| c.len() > 500) {
+        return Err(Error::invalid_input(
+            "css_classes",
+            "Classes are at most 500 characters",
+        ));
+    }
+
+    let roles = if item.visibility == MenuVisibility::Roles {
+        let mut seen = HashSet::new();
+        let roles: Vec<String> = item
+            .roles
+            .iter()
+            .map(|r| r.trim().to_string())
+            .filter(|r| !r.is_empty() && seen.insert(r.clone()))
+            .collect();
+        if roles.is_empty() {
+            return Err(Error::invalid_input(
+                "roles",
+                "Items shown to some roles need at least one role",
+            ));
+        }
+        roles
+    } else {
+        Vec::new()
+    };
+
+    Ok(MenuItemRow {
+        id,
+        menu_id,
+        parent_id,
+        title,
+        url,
+        target,
+        object_type: item.kind.as_str().to_string(),
+        object_id,
+        menu_order,
+        css_classes,
+        visibility: item.visibility.as_str().to_string(),
+        roles: Json(roles),
+    })
+}
+
+/// Custom links are relative, fragments or http(s), mailto or tel URLs
+fn check_url(url: &str) -> Result<()> {
+    let lower = url.to_ascii_lowercase();
+    let allowed = url.starts_with('/')
+        || url.starts_with('#')
+        || url.starts_with('?')
+        || ["http://", "https://", "mailto:", "tel:"]
+            .iter()
+            .any(|scheme| lower.starts_with(scheme));
+    if !allowed {
+        return Err(Error::invalid_input(
+            "url",
+            "Links are relative paths or http(s), mailto or tel URLs",
+        ));
+    }
+    if url.chars().count() > 500 {
+        return Err(Error::invalid_input(
+            "url",
+            "Links are at most 500 characters",
+        ));
+    }
+    Ok(())
+}
+
+/// Items of each menu grouped under their parent, in order
+type Children<'a> = HashMap<(Uuid, Option<Uuid>), Vec<&'a MenuItemRow>>;
+
+fn children_of(rows: &[MenuItemRow]) -> Children<'_> {
+    let mut children: Children = HashMap::new();
+    for row in rows {
+        children
+            .entry((row.menu_id, row.parent_id))
+            .or_default()
+            .push(row);
+    }
+    children
+}
+
+/// Items under a parent (or at the top of a menu) as editors see them
+fn editor_tree(
+    key: (Uuid, Option<Uuid>),
+    children: &Children,
+    targets: &HashMap<Uuid, LinkTarget>,
+) -> Vec<MenuItem> {
+    let Some(rows) = children.get(&key) else {
+        return Vec::new();
+    };
+    rows.iter()
+        .map(|row| {
+            let kind = MenuItemKind::parse(&row.object_type);
+            MenuItem {
+                id: row.id,
+                title: row.title.clone(),
+                kind,
+                url: row.url.clone(),
+                object_id: row.object_id,
+                link: link_of(row, kind, targets),
+                object_title: row
+                    .object_id
+                    .and_then(|id| targets.get(&id))
+                    .map(|t| t.title.clone()),
+                target: row.target.clone(),
+                css_classes: row.css_classes.clone(),
+                visibility: MenuVisibility::parse(&row.visibility),
+                roles: row.roles.0.clone(),
+                children: editor_tree((row.menu_id, Some(row.id)), children, targets),
+            }
+        })
+        .collect()
+}
+
+/// Items under a parent (or at the top of a menu) that a viewer sees
+fn render_tree(
+    key: (Uuid, Option<Uuid>),
+    children: &Children,
+    targets: &HashMap<Uuid, LinkTarget>,
+    viewer: Option<&[String]>,
+) -> Vec<MenuItemData> {
+    let Some(rows) = children.get(&key) else {
+        return Vec::new();
+    };
+    rows.iter()
+        .filter(|row| MenuVisibility::parse(&row.visibility).allows(&row.roles.0, viewer))
+        .filter_map(|row| {
+            let kind = MenuItemKind::parse(&row.object_type);
+            let url = link_of(row, kind, targets)?;
+            let title = if row.title.is_empty() {
+                row.object_id
+                    .and_then(|id| targets.get(&id))
+                    .map(|t| t.title.clone())
+                    .unwrap_or_default()
+            } else {
+                row.title.clone()
+            };
+            let classes = row
+                .css_classes
+                .as_deref()
+                .map(|c| c.split_whitespace().map(str::to_string).collect())
+                .unwrap_or_else(|| vec!["menu-item".to_string()]);
+            Some(MenuItemData {
+                id: row.id.to_string(),
+                title,
+                url,
+                target: row.target.clone(),
+                classes,
+                parent_id: row.parent_id.map(|id| id.to_string()),
+                children: render_tree((row.menu_id, Some(row.id)), children, targets, viewer),
+            })
+        })
+        .collect()
+}
+
+/// Path an item links to; `None` when its post or term is not public
+fn link_of(
+    row: &MenuItemRow,
+    kind: MenuItemKind,
+    targets: &HashMap<Uuid, LinkTarget>,
+) -> Option<String> {
+    match kind {
+        MenuItemKind::Custom => row.url.clone().filter(|u| !u.is_empty()),
+        _ => row
+            .object_id
+            .and_then(|id| targets.get(&id))
+            .map(|t| t.path.clone()),
+    }
+}
+
+/// Menu filling each location: the theme's assignments, then menus naming
+/// a location themselves. The first menu by name wins a location several
+/// menus name.
+fn placements(assigned: &HashMap<String, Uuid>, menus: &[MenuRow]) -> HashMap<String, Uuid> {
+    let mut placed = assigned.clone();
+    for menu in menus {
+        if let Some(ref location) = menu.location {
+            placed.entry(location.clone()).or_insert(menu.id);
+        }
+    }
+    placed
+}
+
+/// Lowercase letters, digits, `-` and `_`
+fn is_location(value: &str) -> bool {
+    !value.is_empty()
+        && value.len() <= 100
+        && value
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+}
+
+fn is_css_class(value: &str) -> bool {
+    value
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn menu_slug(value: &str) -> String {
+    slugify::slugify(value, "", "-", Some(MAX_MENU_SLUG))
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn custom(title: &str, url: &str) -> MenuItemInput {
+        MenuItemInput {
+            title: title.to_string(),
+            url: Some(url.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn menu(id: u128, slug: &str, location: Option<&str>) -> MenuRow {
+        MenuRow {
+            id: Uuid::from_u128(id),
+            name: slug.to_string(),
+            slug: slug.to_string(),
+            description: None,
+            location: location.map(str::to_string),
+            version: 1,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_flatten_puts_parents_first() {
+        let menu_id = Uuid::from_u128(1);
+        let mut about = custom("About", "/page/about");
+        about.children = vec![custom("Team", "/page/team"), {
+            let mut post = MenuItemInput {
+                kind: MenuItemKind::Post,
+                object_id: Some(Uuid::from_u128(7)),
+                ..Default::default()
+            };
+            post.children = vec![custom("Docs", "https://docs.example.com")];
+            post
+        }];
+        let rows = flatten(menu_id, vec![custom("Home", "/"), about]).unwrap();
+
+        let titles: Vec<&str> = rows.iter().map(|r| r.title.as_str()).collect();
+        assert_eq!(titles, ["Home", "About", "Team", "", "Docs"]);
+        assert_eq!(rows[1].parent_id, None);
+        assert_eq!(rows[2].parent_id, Some(rows[1].id));
+        assert_eq!(rows[3].parent_id, Some(rows[1].id));
+        assert_eq!(rows[4].parent_id, Some(rows[3].id));
+        assert_eq!(rows[3].menu_order, 1);
+        assert_eq!(rows[3].object_type, "post");
+        assert_eq!(rows[3].url, None);
+    }
+
+    #[test]
+    fn test_item_validation() {
+        let menu_id = Uuid::nil();
+        assert!(flatten(menu_id, vec![custom("Home", "")]).is_err());
+        assert!(flatten(menu_id, vec![custom("", "/")]).is_err());
+        assert!(flatten(menu_id, vec![custom("X", "javascript:alert(1)")]).is_err());
+        assert!(flatten(menu_id, vec![custom("Mail", "mailto:hi@example.com")]).is_ok());
+
+        let term = MenuItemInput {
+            kind: MenuItemKind::Term,
+            ..Default::default()
+        };
+        assert!(flatten(menu_id, vec![term]).is_err());
+
+        let mut members = custom("Members", "/members");
+        members.visibility = MenuVisibility::Roles;
+        members.roles = vec![" ".to_string()];
+        assert!(flatten(menu_id, vec![members.clone()]).is_err());
+        members.roles = vec!["editor".to_string(), "editor".to_string()];
+        assert_eq!(
+            flatten(menu_id, vec![members]).unwrap()[0].roles.0,
+            ["editor"]
+        );
+
+        let mut blank = custom("Out", "https://example.com");
+        blank.target = Some("_top".to_string());
+        assert!(flatten(menu_id, vec![blank]).is_err());
+
+        let mut deep = custom("Leaf", "/");
+        for _ in 0..MAX_DEPTH {
+            let mut parent = custom("Level", "/");
+            parent.children = vec![deep];
+            deep = parent;
+        }
+        assert!(flatten(menu_id, vec![deep]).is_err());
+    }
+
+    #[test]
+    fn test_visibility() {
+        let roles = vec!["editor".to_string()];
+        let editor = vec!["editor".to_string()];
+        let author = vec!["author".to_string()];
+
+        assert!(MenuVisibility::Everyone.allows(&[], None));
+        assert!(MenuVisibility::LoggedIn.allows(&[], Some(&author)));
+        assert!(!MenuVisibility::LoggedIn.allows(&[], None));
+        assert!(MenuVisibility::LoggedOut.allows(&[], None));
+        assert!(!MenuVisibility::LoggedOut.allows(&[], Some(&author)));
+        assert!(MenuVisibility::Roles.allows(&roles, Some(&editor)));
+        assert!(!MenuVisibility::Roles.allows(&roles, Some(&author)));
+        assert!(!MenuVisibility::Roles.allows(&roles, None));
+    }
+
+    #[test]
+    fn test_render_tree() {
+        let menu_id = Uuid::from_u128(1);
+        let post_id = Uuid::from_u128(7);
+        let draft_id = Uuid::from_u128(8);
+
+        let mut account = custom("Account", "/account");
+        account.visibility = MenuVisibility::LoggedIn;
+        account.children = vec![custom("Settings", "/account/settings")];
+        let mut news = MenuItemInput {
+            kind: MenuItemKind::Post,
+            object_id: Some(post_id),
+            css_classes: Some("highlight  news".to_string()),
+            ..Default::default()
+        };
+        news.children = vec![MenuItemInput {
+            kind: MenuItemKind::Post,
+            object_id: Some(draft_id),
+            ..Default::default()
+        }];
+        let rows = flatten(menu_id, vec![news, account]).unwrap();
+
+        let targets = HashMap::from([(
+            post_id,
+            LinkTarget {
+                path: "/post/news".to_string(),
+                title: "News".to_string(),
+            },
+        )]);
+        let children = children_of(&rows);
+
+        let anonymous = render_tree((menu_id, None), &children, &targets, None);
+        assert_eq!(anonymous.len(), 1);
+        assert_eq!(anonymous[0].title, "News");
+        assert_eq!(anonymous[0].url, "/post/news");
+        assert_eq!(anonymous[0].classes, ["highlight", "news"]);
+        // The draft is left out
+        assert!(anonymous[0].children.is_empty());
+
+        let roles = vec!["subscriber".to_string()];
+        let member = render_tree((menu_id, None), &children, &targets, Some(&roles));
+        assert_eq!(member.len(), 2);
+        assert_eq!(member[1].children[0].title, "Settings");
+        assert_eq!(member[1].classes, ["menu-item"]);
+
+        let editor = editor_tree((menu_id, None), &children, &targets);
+        assert_eq!(editor[0].link.as_deref(), Some("/post/news"));
+        assert_eq!(editor[0].children[0].link, None);
+    }
+
+    #[test]
+    fn test_placements() {
+        let assigned = HashMap::from([("primary".to_string(), Uuid::from_u128(2))]);
+        let menus = vec![
+            menu(1, "main", Some("primary")),
+            menu(3, "footer-links", Some("footer")),
+            menu(4, "legal", Some("footer")),
+        ];
+        let placed = placements(&assigned, &menus);
+        assert_eq!(placed["primary"], Uuid::from_u128(2));
+        assert_eq!(placed["footer"], Uuid::from_u128(3));
+        assert_eq!(placed.len(), 2);
+    }
+}
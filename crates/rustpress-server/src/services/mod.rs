@@ -2,9 +2,64 @@
 //!
 //! Contains service layers that coordinate between handlers and repositories.
 
+pub mod abuse_challenge;
+pub mod admin_search;
+pub mod archives;
+pub mod author_analytics;
+pub mod avatar;
+pub mod cache_policy;
+pub mod cache_warmer;
+pub mod captcha;
+pub mod change_feed;
+pub mod compat;
+pub mod compliance;
+pub mod content_filters;
+pub mod content_performance;
+pub mod content_sanitization;
+pub mod device_login;
+pub mod discussions;
 pub mod email_service;
+pub mod events;
+pub mod export_service;
+pub mod extension_allowlists;
+pub mod feeds;
+pub mod geoip;
+pub mod groups;
+pub mod http_signatures;
+pub mod inbound_email;
+pub mod indexing;
+pub mod json_setting;
+pub mod menus;
+pub mod og_image;
+pub mod page_cache;
+pub mod plugin_settings;
+pub mod podcast;
+pub mod public_api;
+pub mod read_only;
+pub mod redirects;
+pub mod regions;
+pub mod render_migration;
 pub mod render_service;
+pub mod responsive_images;
+pub mod robots;
+pub mod saml_sso;
+pub mod saved_views;
+pub mod search;
+pub mod setting_approvals;
+pub mod settings_sync;
+pub mod site_bundle;
+pub mod sites;
+pub mod social;
+pub mod taxonomy;
 pub mod theme_service;
+pub mod usage;
+pub mod user_api_keys;
+pub mod user_import;
+pub mod user_profile;
+pub mod web_vitals;
+pub mod webhooks;
+pub mod widgets;
+pub mod wordpress_import;
 
 pub use theme_service::{
     DefaultThemeInfo, ThemeInfo, ThemeInstallResult, ThemePreviewResult, ThemeScanResult,
@@ -16,4 +71,207 @@ pub use render_service::{
     RenderService, RenderedPage, SiteInfo, TermData, WidgetAreaData, WidgetData,
 };
 
+pub use abuse_challenge::{
+    AbuseChallengeConfig, AbuseChallengeService, ChallengeFailure, EndpointChallenge,
+    IssuedChallenge,
+};
+
+pub use admin_search::{
+    AdminRole, AdminSearchHit, AdminSearchQuery, AdminSearchResults, AdminSearchService,
+    SearchKind, SearchUser, TrashFilter,
+};
+
+pub use site_bundle::{
+    AnalyticsExporter, PluginImportResult, PluginImportStatus, SiteBundle, SiteBundleService,
+};
+
+pub use search::{
+    DocumentKind, ReindexReport, SearchDocument, SearchHit, SearchQuery, SearchResults,
+    SearchService, SearchStats,
+};
+
+pub use archives::{ArchiveQuery, ArchiveTerm, DateArchive};
+
+pub use feeds::{FeedFormat, FeedScope, FeedValidators, RenderedFeed};
+
+pub use author_analytics::{
+    AnalyticsScope, AuthorAnalytics, AuthorAnalyticsService, AuthorLeaderboard, AuthorPost,
+    AuthorSort, AuthorStats, DailyViews, LeaderboardQuery,
+};
+
+pub use cache_policy::{
+    CacheHints, CacheOverride, CachePolicy, CachePolicyConfig, CachePolicyEngine,
+    CachePolicyService, SurrogateKeys,
+};
+
+pub use render_migration::{
+    RenderComparison, RenderMigrationAssist, RenderMigrationConfig, RenderMigrationReport,
+    RenderPipeline,
+};
+
+pub use robots::{RobotsConfig, SiteSection};
+
+pub use cache_warmer::{
+    CacheWarmerConfig, CacheWarmerService, WarmPageCacheHandler, WarmPageCacheJob, WarmReason,
+    WarmRun, WarmTarget,
+};
+
+pub use captcha::{
+    CaptchaConfig, CaptchaFailure, CaptchaOutcome, CaptchaProvider, CaptchaService,
+    CaptchaVerification, CaptchaVerifier, EndpointCaptcha,
+};
+
+pub use saml_sso::{SamlSsoConfig, SamlSsoService, SAML_SSO_SETTINGS_KEY};
+
+pub use compliance::{
+    ComplianceAction, ComplianceConfig, ComplianceDecision, ComplianceEngine, ComplianceRule,
+    ComplianceService, ContentDescriptor, CookieBannerVariant, RegionContext,
+};
+
+pub use content_performance::{
+    ContentPerformance, ContentPerformanceQuery, ContentPerformanceReport,
+    ContentPerformanceService, ContentSort,
+};
+
+pub use content_filters::{ContentFilterService, ContentFiltersConfig};
+
+pub use content_sanitization::{
+    ContentSanitizationService, ContentSource, LegacySweep, ReportQuery, RolePolicy,
+    SanitizationPolicy, SanitizationReport, SanitizedContent,
+};
+
+pub use device_login::{device_login_provider, DeviceLoginProvider, CLI_CLIENT_ID};
+
+pub use discussions::{
+    CommentInput, DiscussionComment, DiscussionQuery, DiscussionService, DiscussionThread,
+    ThreadFilter, ThreadInput, ThreadStatus,
+};
+
 pub use email_service::{EmailConfig, EmailError, EmailResult, EmailService, EmailTemplate};
+
+pub use events::{CalendarQuery, EventInput, EventService, EventStatus, Venue};
+
+pub use geoip::{
+    GeoIpConfig, GeoIpPrivacy, GeoIpProvider, GeoIpService, GeoIpStatus, GeoLocation,
+    UpdateGeoIpDatabaseHandler, UpdateGeoIpDatabaseJob,
+};
+
+pub use groups::{
+    GroupGrants, GroupInput, GroupMember, GroupRecipient, GroupSection, GroupService, UserGroup,
+};
+
+pub use http_signatures::{
+    HttpSignatureKey, HttpSignatureService, InboundKeyInput, KeyDirection, OutboundKeyInput,
+    SignatureAlgorithm, SignatureError, VerifiedSignature,
+};
+
+pub use indexing::{
+    IndexedUrl, IndexingAction, IndexingConfig, IndexingReport, IndexingReportQuery,
+    IndexingService, IndexingSubmission, SubmitIndexingHandler, SubmitIndexingJob,
+    SubmitUrlsRequest, VerificationProvider,
+};
+
+pub use page_cache::{CachedPage, PageCacheConfig, PageCacheService, PageCacheStats};
+
+pub use public_api::{
+    CreatedPublicApiKey, PublicApiKey, PublicApiKeyInput, PublicApiService, PublicApiUsageReport,
+};
+
+pub use read_only::{ReadOnlyService, ReadOnlyStatus, ReadOnlyUpdate};
+
+pub use redirects::{
+    content_path, encode_location, MatchType, NewRedirect, Redirect, RedirectInput, RedirectQuery,
+    RedirectService, ResolvedRedirect,
+};
+
+pub use menus::{
+    Menu, MenuInput, MenuItem, MenuItemInput, MenuItemKind, MenuItemsInput, MenuLocation,
+    MenuService, MenuVisibility,
+};
+
+pub use inbound_email::{
+    InboundEmailService, InboundMailbox, InboundMailboxInput, InboundMailboxUpdate, InboundMessage,
+    InboundMessageQuery, InboundMessageStatus, MailboxKind, ParsedEmail, PollInboundEmailHandler,
+    PollInboundEmailJob, PollReport,
+};
+
+pub use webhooks::{
+    CreatedWebhookEndpoint, DeliverWebhookHandler, DeliverWebhookJob, DeliveryQuery,
+    DeliveryStatus, WebhookDelivery, WebhookDeliveryDetail, WebhookEndpoint, WebhookEndpointInput,
+    WebhookEndpointUpdate, WebhookService,
+};
+
+pub use web_vitals::{
+    Device, EvaluateWebVitalsBudgetsHandler, EvaluateWebVitalsBudgetsJob, VitalMetric, VitalsAlert,
+    VitalsAlertQuery, VitalsBeacon, VitalsBudget, WebVitalsConfig, WebVitalsQuery, WebVitalsReport,
+    WebVitalsService,
+};
+
+pub use responsive_images::{
+    MediaDerivative, ResponsiveImagesConfig, ResponsiveImagesService,
+    RESPONSIVE_IMAGES_SETTINGS_KEY,
+};
+
+pub use widgets::{WidgetArea, WidgetInput, WidgetPlacement, WidgetService, CLASSIC_WIDGETS};
+
+pub use sites::{CurrentSite, SiteAddress, SiteInput, SiteService, SiteStatus};
+
+pub use usage::{SitePlan, SitePlanInput, SiteUsage, SiteUsageReport, UsageService};
+
+pub use og_image::{og_image_path, OgImageService};
+
+pub use podcast::{
+    ByteRange, DownloadQuery, DownloadRequest, EpisodeAudio, EpisodeInput, PodcastConfig,
+    PodcastService,
+};
+
+pub use social::{
+    CardImage, CardRasterizer, DispatchSocialSharesHandler, DispatchSocialSharesJob,
+    PostSocialInput, ShareQuery, ShareRequest, SocialConfig, SocialConnector, SocialNetwork,
+    SocialService,
+};
+
+pub use taxonomy::{
+    ReassignInput, SplitInput, Taxonomy, TaxonomyInput, TaxonomyService, Term, TermInput, TermQuery,
+};
+
+pub use settings_sync::{SettingsChange, SettingsSync, SETTINGS_CHANNEL};
+
+pub use setting_approvals::{
+    ApprovalConfig, ChangeRequest, ChangeRequestEvent, ChangeRequestStatus, SettingApprovalService,
+    SettingDiff,
+};
+
+pub use change_feed::{ChangeFeed, ChangeFeedStats, RowChange, CHANGES_CHANNEL, RESYNC_EVENT};
+
+pub use export_service::{ExportDataset, ExportFormat, ExportParams, ExportService};
+
+pub use extension_allowlists::{
+    AllowlistEntry, AllowlistScope, AllowlistUpdate, ExtensionAllowlistService,
+};
+
+pub use avatar::{avatar_url, AvatarService, HasAvatar, ResolvedAvatar};
+
+pub use saved_views::{
+    ListResource, SavedView, SavedViewInput, SavedViewList, SavedViewService, ViewSortOrder,
+    ViewUser,
+};
+
+pub use user_api_keys::{
+    ApiCapabilities, ApiKeyOwner, CreatedUserApiKey, UserApiKey, UserApiKeyInput, UserApiKeyService,
+};
+
+pub use user_import::{
+    DuplicatePolicy, ImportFormat, UserImportOptions, UserImportRequest, UserImportRun,
+    UserImportService,
+};
+
+pub use user_profile::{
+    AvatarSource, FieldVisibility, ProfileFieldDefinition, ProfileFieldsConfig, ProfileService,
+    ProfileUpdate, ProfileView, ProfileViewer,
+};
+
+pub use wordpress_import::{
+    ImportItemError, WordpressImportOptions, WordpressImportRun, WordpressImportService,
+    MAX_WXR_SIZE,
+};
@@ -0,0 +1,312 @@
+//! Open Graph Images
+//!
+//! Serves the generated `og:image` of every post at `/og-image/{id}`:
+//!
+//! - the image is the post's preview card (see [`super::social`]), built
+//!   from the configured template with its title, author, category colour
+//!   and the site logo
+//! - with a rasterizer configured, cards are turned into PNGs when a post
+//!   is published or updated, or on the first request, and kept in storage
+//!   under `og-images/{post_id}/` along with the hash of the SVG they were
+//!   made from; a card is only rasterized again when its SVG changes
+//! - without one, the SVG is served as is
+//!
+//! Themes and the SEO endpoints should build URLs with [`og_image_path`]
+//! or the `og_image_url(post=post)` template function, whose version
+//! parameter changes with the post so networks refetch the image.
+
+use bytes::Bytes;
+use parking_lot::Mutex;
+use rustpress_core::error::{Error, Result};
+use rustpress_events::subscriber::SubscriberConfig;
+use rustpress_events::{EventBus, EventType, Subscriber};
+use rustpress_storage::Storage;
+use sha2::{Digest, Sha256};
+use sqlx::{FromRow, PgPool};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+use super::change_feed::{ChangeOp, RowChange};
+use super::social::{CardImage, SocialService};
+
+/// Images kept in memory
+const IMAGE_CACHE_CAPACITY: usize = 256;
+
+/// How long an image is reused from memory
+const IMAGE_CACHE_TTL: Duration = Duration::from_secs(3600);
+
+/// Path of a post's Open Graph image; the version changes with the post so
+/// networks refetch it
+pub fn og_image_path(post_id: Uuid, updated_at: &str) -> String {
+    let digest = Sha256::digest(updated_at.as_bytes());
+    let version: String = digest
+        .iter()
+        .take(4)
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    format!("/og-image/{}?v={}", post_id, version)
+}
+
+/// Tera function: `og_image_url(post=post)`, also registered as
+/// `social_card_url`
+pub fn tera_og_image_url(args: &HashMap<String, tera::Value>) -> tera::Result<tera::Value> {
+    let post = args
+        .get("post")
+        .ok_or_else(|| tera::Error::msg("og_image_url: missing 'post' argument"))?;
+    let id = post
+        .get("id")
+        .and_then(|v| v.as_str())
+        .and_then(|id| Uuid::parse_str(id).ok())
+        .ok_or_else(|| tera::Error::msg("og_image_url: 'post' has no id"))?;
+    let updated_at = post
+        .get("updated_at")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default();
+    Ok(tera::Value::String(og_image_path(id, updated_at)))
+}
+
+/// A stored image of a post
+#[derive(Debug, FromRow)]
+struct StoredImage {
+    svg_hash: String,
+    path: String,
+    content_type: String,
+}
+
+/// An image and the SVG it was made from
+struct CachedImage {
+    svg_hash: String,
+    image: CardImage,
+    at: Instant,
+}
+
+/// Rasterized preview cards behind `/og-image/{id}`
+pub struct OgImageService {
+    pool: PgPool,
+    storage: Arc<Storage>,
+    social: Arc<SocialService>,
+    cache: Mutex<HashMap<Uuid, CachedImage>>,
+}
+
+impl OgImageService {
+    pub fn new(pool: PgPool, storage: Arc<Storage>, social: Arc<SocialService>) -> Self {
+        Self {
+            pool,
+            storage,
+            social,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Image of a post; only published posts unless `unpublished` is set.
+    /// Falls back to the SVG when rasterizing fails.
+    pub async fn image(&self, post_id: Uuid, unpublished: bool) -> Result<CardImage> {
+        let svg = self.social.card_svg(post_id, unpublished).await?;
+        let Some(rasterizer) = self.social.rasterizer().await else {
+            return Ok(CardImage {
+                content_type: "image/svg+xml".to_string(),
+                bytes: Bytes::from(svg),
+            });
+        };
+
+        let svg_hash = format!("{:x}", Sha256::digest(svg.as_bytes()));
+        if let Some(cached) = self.cache.lock().get(&post_id) {
+            if cached.svg_hash == svg_hash && cached.at.elapsed() < IMAGE_CACHE_TTL {
+                return Ok(cached.image.clone());
+            }
+        }
+
+        let stored = self.stored(post_id).await?;
+        if let Some(stored) = stored.as_ref().filter(|s| s.svg_hash == svg_hash) {
+            match self.storage.get(&stored.path).await {
+                Ok(bytes) => {
+                    let image = CardImage {
+                        content_type: stored.content_type.clone(),
+                        bytes,
+                    };
+                    self.remember(post_id, svg_hash, &image);
+                    return Ok(image);
+                }
+                Err(e) => {
+                    tracing::warn!(post_id = %post_id, "Stored OG image is missing, regenerating: {}", e);
+                }
+            }
+        }
+
+        let image = match rasterizer.rasterize(&svg).await {
+            Ok(image) => image,
+            Err(e) => {
+                tracing::warn!(post_id = %post_id, "Card rasterization failed, serving SVG: {}", e);
+                return Ok(CardImage {
+                    content_type: "image/svg+xml".to_string(),
+                    bytes: Bytes::from(svg),
+                });
+            }
+        };
+
+        // Failing to store only costs a rasterization next time
+        if let Err(e) = self
+            .store(post_id, &svg_hash, &image, stored.map(|s| s.path))
+            .await
+        {
+            tracing::warn!(post_id = %post_id, "Failed to store OG image: {}", e);
+        }
+        self.remember(post_id, svg_hash, &image);
+        Ok(image)
+    }
+
+    /// Forget a post's images, in memory and in storage
+    pub async fn discard(&self, post_id: Uuid) -> Result<()> {
+        self.cache.lock().remove(&post_id);
+        sqlx::query("DELETE FROM og_images WHERE post_id = $1")
+            .bind(post_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to delete OG image", e))?;
+        for path in self.storage.list(&format!("og-images/{}", post_id)).await? {
+            self.storage.delete(&path).await?;
+        }
+        Ok(())
+    }
+
+    async fn stored(&self, post_id: Uuid) -> Result<Option<StoredImage>> {
+        sqlx::query_as("SELECT svg_hash, path, content_type FROM og_images WHERE post_id = $1")
+            .bind(post_id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to load OG image", e))
+    }
+
+    async fn store(
+        &self,
+        post_id: Uuid,
+        svg_hash: &str,
+        image: &CardImage,
+        previous: Option<String>,
+    ) -> Result<()> {
+        let extension = match image.content_type.as_str() {
+            "image/jpeg" => "jpg",
+            "image/webp" => "webp",
+            _ => "png",
+        };
+        let file = self
+            .storage
+            .upload_to(
+                image.bytes.clone(),
+                &format!("og-image.{}", extension),
+                &image.content_type,
+                &format!("og-images/{}", post_id),
+            )
+            .await?;
+
+        let saved = sqlx::query(
+            "INSERT INTO og_images (post_id, svg_hash, path, content_type, size) \
+             VALUES ($1, $2, $3, $4, $5) \
+             ON CONFLICT (post_id) DO UPDATE SET svg_hash = EXCLUDED.svg_hash, \
+             path = EXCLUDED.path, content_type = EXCLUDED.content_type, \
+             size = EXCLUDED.size, created_at = NOW()",
+        )
+        .bind(post_id)
+        .bind(svg_hash)
+        .bind(&file.path)
+        .bind(&image.content_type)
+        .bind(image.bytes.len() as i64)
+        .execute(&self.pool)
+        .await;
+        if let Err(e) = saved {
+            let _ = self.storage.delete(&file.path).await;
+            return Err(Error::database_with_source("Failed to save OG image", e));
+        }
+
+        if let Some(previous) = previous.filter(|p| *p != file.path) {
+            if let Err(e) = self.storage.delete(&previous).await {
+                tracing::warn!(post_id = %post_id, "Failed to delete old OG image: {}", e);
+            }
+        }
+        Ok(())
+    }
+
+    fn remember(&self, post_id: Uuid, svg_hash: String, image: &CardImage) {
+        let mut cache = self.cache.lock();
+        if cache.len() >= IMAGE_CACHE_CAPACITY && !cache.contains_key(&post_id) {
+            if let Some(oldest) = cache.iter().min_by_key(|(_, c)| c.at).map(|(id, _)| *id) {
+                cache.remove(&oldest);
+            }
+        }
+        cache.insert(
+            post_id,
+            CachedImage {
+                svg_hash,
+                image: image.clone(),
+                at: Instant::now(),
+            },
+        );
+    }
+
+    /// Generate images when posts are published or updated, and remove
+    /// them with their posts
+    pub fn subscribe(self: &Arc<Self>, bus: &EventBus) {
+        let event_types = ["created", "updated", "deleted"]
+            .iter()
+            .map(|op| EventType::new(format!("change.post.{}", op)))
+            .collect();
+
+        let images = Arc::downgrade(self);
+        bus.subscribe(Subscriber::new(
+            "og_image_generation",
+            SubscriberConfig::new(event_types).async_handler(),
+            move |event| {
+                let images = images.clone();
+                async move {
+                    let Some(images) = images.upgrade() else {
+                        return Ok(());
+                    };
+                    let Ok(change) = serde_json::from_value::<RowChange>(event.payload.clone())
+                    else {
+                        return Ok(());
+                    };
+                    let Some(post_id) = change.id else {
+                        return Ok(());
+                    };
+                    let result = match change.op {
+                        ChangeOp::Delete => images.discard(post_id).await,
+                        _ => match images.image(post_id, false).await {
+                            // Drafts get an image when they are published
+                            Ok(_) | Err(Error::NotFound { .. }) => Ok(()),
+                            Err(e) => Err(e),
+                        },
+                    };
+                    if let Err(e) = result {
+                        tracing::warn!(post_id = %post_id, "Failed to update OG image: {}", e);
+                    }
+                    Ok(())
+                }
+            },
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_og_image_path() {
+        let id = Uuid::nil();
+        let path = og_image_path(id, "2025-01-01T00:00:00Z");
+        assert!(path.starts_with("/og-image/00000000-0000-0000-0000-000000000000?v="));
+        assert_eq!(path.len(), "/og-image/".len() + 36 + "?v=".len() + 8);
+        assert_ne!(path, og_image_path(id, "2025-01-02T00:00:00Z"));
+
+        let mut args = HashMap::new();
+        args.insert(
+            "post".to_string(),
+            serde_json::json!({ "id": id.to_string(), "updated_at": "2025-01-01T00:00:00Z" }),
+        );
+        assert_eq!(tera_og_image_url(&args).unwrap(), tera::Value::String(path));
+        args.insert("post".to_string(), serde_json::json!({ "title": "No id" }));
+        assert!(tera_og_image_url(&args).is_err());
+    }
+}
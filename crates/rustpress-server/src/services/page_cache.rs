@@ -0,0 +1,543 @@
+//! Full-Page Cache
+//!
+//! Keeps rendered public pages in the `rustpress-cache` backend so repeat
+//! anonymous requests are answered without the render service or Postgres.
+//! Entries are keyed by host, path, normalised query string and the
+//! configured vary headers, and only responses the cache policy made
+//! public are stored.
+//!
+//! Invalidation reuses the surrogate keys rendered pages already carry
+//! (see [`SurrogateKeys`](super::cache_policy::SurrogateKeys)). Pages are
+//! stored tagged with their keys, so purging a key deletes every list,
+//! archive and feed showing the changed content. Purging also records
+//! when it happened; a page whose render started before the latest purge
+//! of any of its keys, and was stored after the purge, is treated as a
+//! miss.
+
+use base64::Engine as _;
+use chrono::Utc;
+use rustpress_cache::{Cache, CacheKey};
+use rustpress_core::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use std::collections::BTreeSet;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use super::json_setting::JsonSetting;
+
+/// Settings key holding the page cache configuration
+pub const PAGE_CACHE_SETTINGS_KEY: &str = "page_cache";
+
+/// Stored page cache settings
+const PAGE_CACHE_SETTING: JsonSetting<PageCacheConfig> =
+    JsonSetting::new(PAGE_CACHE_SETTINGS_KEY, "cache", "page cache settings");
+
+/// Surrogate key every entry carries; purging it empties the page cache
+pub const ALL_PAGES_KEY: &str = "all";
+
+/// Longest time a page may be kept
+pub const MAX_PAGE_TTL_SECS: u32 = 86400;
+
+const ENTRY_PREFIX: &str = "page_cache:entry:";
+const PURGED_PREFIX: &str = "page_cache:purged:";
+const TAG_PREFIX: &str = "page_cache:";
+
+/// Purge markers must outlive every entry they invalidate
+const PURGE_MARKER_TTL: Duration = Duration::from_secs(2 * MAX_PAGE_TTL_SECS as u64);
+
+/// Response headers never replayed from the cache
+const UNCACHED_HEADERS: [&str; 6] = [
+    "age",
+    "content-length",
+    "date",
+    "set-cookie",
+    "x-page-cache",
+    "x-request-id",
+];
+
+/// Page cache settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PageCacheConfig {
+    pub enabled: bool,
+    /// Upper bound for how long a page is kept; a shorter `s-maxage` or
+    /// `max-age` from the cache policy wins
+    pub ttl_secs: u32,
+    /// Request headers whose values split the cache
+    pub vary_headers: Vec<String>,
+    /// Query parameters ignored when building the key; `prefix*` matches
+    /// every parameter starting with `prefix`
+    pub ignored_query_params: Vec<String>,
+    /// Requests carrying any of these cookies always bypass the cache
+    pub bypass_cookies: Vec<String>,
+    /// Larger responses are not stored
+    pub max_body_bytes: usize,
+}
+
+impl Default for PageCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            ttl_secs: 600,
+            // Compression runs inside the cache, so bodies differ by encoding
+            vary_headers: vec!["accept-encoding".to_string()],
+            ignored_query_params: vec![
+                "utm_*".to_string(),
+                "fbclid".to_string(),
+                "gclid".to_string(),
+                "mc_cid".to_string(),
+                "mc_eid".to_string(),
+            ],
+            bypass_cookies: Vec::new(),
+            max_body_bytes: 2 * 1024 * 1024,
+        }
+    }
+}
+
+impl PageCacheConfig {
+    /// Load the configuration from settings, falling back to defaults
+    pub async fn load(pool: &PgPool) -> Result<Self> {
+        PAGE_CACHE_SETTING.load_or_default(pool).await
+    }
+
+    /// Persist the configuration to settings
+    pub async fn save(&self, pool: &PgPool) -> Result<()> {
+        PAGE_CACHE_SETTING.save(pool, self).await
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.ttl_secs == 0 || self.ttl_secs > MAX_PAGE_TTL_SECS {
+            return Err(Error::invalid_input(
+                "ttl_secs",
+                format!("TTL must be between 1 and {} seconds", MAX_PAGE_TTL_SECS),
+            ));
+        }
+        if let Some(name) = self
+            .vary_headers
+            .iter()
+            .find(|name| axum::http::HeaderName::from_bytes(name.as_bytes()).is_err())
+        {
+            return Err(Error::invalid_input(
+                "vary_headers",
+                format!("'{}' is not a valid header name", name),
+            ));
+        }
+        Ok(())
+    }
+
+    fn ignores_param(&self, name: &str) -> bool {
+        self.ignored_query_params
+            .iter()
+            .any(|pattern| match pattern.strip_suffix('*') {
+                Some(prefix) => name.starts_with(prefix),
+                None => name == pattern,
+            })
+    }
+
+    /// Whether a request carries one of the bypass cookies
+    pub fn has_bypass_cookie<'a>(&self, cookie_headers: impl Iterator<Item = &'a str>) -> bool {
+        if self.bypass_cookies.is_empty() {
+            return false;
+        }
+        cookie_headers
+            .flat_map(|header| header.split(';'))
+            .filter_map(|pair| pair.trim().split_once('='))
+            .any(|(name, _)| self.bypass_cookies.iter().any(|c| c == name))
+    }
+
+    /// Cache key for a request.
+    ///
+    /// `header` looks up a request header by lowercase name.
+    pub fn cache_key<'a>(
+        &self,
+        host: &str,
+        path: &str,
+        query: Option<&str>,
+        header: impl Fn(&str) -> Option<&'a str>,
+    ) -> String {
+        let mut params: Vec<&str> = query
+            .unwrap_or_default()
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .filter(|pair| !self.ignores_param(pair.split('=').next().unwrap_or(pair)))
+            .collect();
+        params.sort_unstable();
+
+        let mut material = format!(
+            "{}\n{}\n{}",
+            host.to_ascii_lowercase(),
+            path,
+            params.join("&")
+        );
+        for name in &self.vary_headers {
+            let name = name.to_ascii_lowercase();
+            material.push('\n');
+            material.push_str(&name);
+            material.push(':');
+            material.push_str(header(&name).unwrap_or_default().trim());
+        }
+
+        format!("{}{:x}", ENTRY_PREFIX, Sha256::digest(material.as_bytes()))
+    }
+}
+
+/// Shared lifetime from a `Cache-Control` value: `s-maxage`, else
+/// `max-age`; `None` unless the response is public
+pub fn shared_max_age(cache_control: &str) -> Option<u32> {
+    let directives: Vec<&str> = cache_control.split(',').map(str::trim).collect();
+    if !directives.iter().any(|d| d.eq_ignore_ascii_case("public")) {
+        return None;
+    }
+    let value = |name: &str| {
+        directives.iter().find_map(|d| {
+            d.split_once('=')
+                .filter(|(key, _)| key.trim().eq_ignore_ascii_case(name))
+                .and_then(|(_, value)| value.trim().parse::<u32>().ok())
+        })
+    };
+    value("s-maxage").or_else(|| value("max-age"))
+}
+
+/// A stored response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedPage {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    /// Base64, since bodies may already be compressed
+    body: String,
+    /// Surrogate keys the page was rendered with
+    pub keys: Vec<String>,
+    /// When rendering started, in milliseconds since the epoch
+    pub rendered_at: i64,
+}
+
+impl CachedPage {
+    pub fn new(
+        status: u16,
+        headers: impl IntoIterator<Item = (String, String)>,
+        body: &[u8],
+        keys: Vec<String>,
+        rendered_at: i64,
+    ) -> Self {
+        Self {
+            status,
+            headers: headers
+                .into_iter()
+                .filter(|(name, _)| !UNCACHED_HEADERS.contains(&name.to_ascii_lowercase().as_str()))
+                .collect(),
+            body: base64::engine::general_purpose::STANDARD.encode(body),
+            keys,
+            rendered_at,
+        }
+    }
+
+    pub fn body(&self) -> Vec<u8> {
+        base64::engine::general_purpose::STANDARD
+            .decode(&self.body)
+            .unwrap_or_default()
+    }
+
+    /// Seconds since the page was rendered
+    pub fn age(&self) -> u64 {
+        (Utc::now().timestamp_millis() - self.rendered_at).max(0) as u64 / 1000
+    }
+
+    /// Fresh unless one of its keys was purged after rendering started
+    fn is_fresh(&self, purged_at: &[Option<i64>]) -> bool {
+        purged_at
+            .iter()
+            .flatten()
+            .all(|purged| *purged < self.rendered_at)
+    }
+}
+
+/// Page cache counters since startup
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PageCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub stores: u64,
+    pub purges: u64,
+}
+
+/// Full-page cache backed by the shared cache
+pub struct PageCacheService {
+    pool: PgPool,
+    cache: Arc<Cache>,
+    config: RwLock<Option<Arc<PageCacheConfig>>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    stores: AtomicU64,
+    purges: AtomicU64,
+}
+
+impl PageCacheService {
+    pub fn new(pool: PgPool, cache: Arc<Cache>) -> Self {
+        Self {
+            pool,
+            cache,
+            config: RwLock::new(None),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            stores: AtomicU64::new(0),
+            purges: AtomicU64::new(0),
+        }
+    }
+
+    /// Configuration, loaded from settings on first use
+    pub async fn config(&self) -> Arc<PageCacheConfig> {
+        if let Some(config) = self.config.read().await.clone() {
+            return config;
+        }
+
+        let config = PageCacheConfig::load(&self.pool).await.unwrap_or_else(|e| {
+            tracing::warn!("Failed to load page cache settings, using defaults: {}", e);
+            PageCacheConfig::default()
+        });
+        let config = Arc::new(config);
+        *self.config.write().await = Some(config.clone());
+        config
+    }
+
+    /// Validate, persist and apply new settings; drops every cached page
+    pub async fn set_config(&self, config: PageCacheConfig) -> Result<()> {
+        config.validate()?;
+        config.save(&self.pool).await?;
+        *self.config.write().await = Some(Arc::new(config));
+        self.purge_all().await
+    }
+
+    /// Drop the cached settings; the next use reloads them
+    pub async fn invalidate(&self) {
+        *self.config.write().await = None;
+    }
+
+    /// A fresh cached page for the key
+    pub async fn lookup(&self, key: &str) -> Option<CachedPage> {
+        let page = match self.cache.get::<CachedPage>(key).await {
+            Ok(Some(page)) => page,
+            Ok(None) => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                return None;
+            }
+            Err(e) => {
+                tracing::warn!("Page cache lookup failed: {}", e);
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                return None;
+            }
+        };
+
+        let markers: Vec<CacheKey> = page
+            .keys
+            .iter()
+            .map(String::as_str)
+            .chain([ALL_PAGES_KEY])
+            .map(purge_marker)
+            .collect();
+        let purged_at = self
+            .cache
+            .get_many::<i64>(&markers)
+            .await
+            .unwrap_or_else(|_| vec![Some(i64::MAX)]);
+
+        if page.is_fresh(&purged_at) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            Some(page)
+        } else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            let _ = self.cache.delete(key).await;
+            None
+        }
+    }
+
+    pub async fn store(&self, key: &str, page: &CachedPage, ttl: Duration) {
+        let tags: Vec<String> = page
+            .keys
+            .iter()
+            .map(|key| page_tag(key))
+            .chain([page_tag(ALL_PAGES_KEY)])
+            .collect();
+        match self.cache.set_with_tags(key, page, Some(ttl), &tags).await {
+            Ok(()) => {
+                self.stores.fetch_add(1, Ordering::Relaxed);
+            }
+            Err(e) => tracing::warn!("Failed to store page in cache: {}", e),
+        }
+    }
+
+    /// Drop every page rendered with any of the surrogate keys
+    pub async fn purge_keys(&self, keys: &[String]) -> Result<()> {
+        let now = Utc::now().timestamp_millis();
+        for key in keys.iter().collect::<BTreeSet<_>>() {
+            self.cache.invalidate_tag(&page_tag(key)).await?;
+            self.cache
+                .set(purge_marker(key), &now, Some(PURGE_MARKER_TTL))
+                .await?;
+        }
+        self.purges.fetch_add(keys.len() as u64, Ordering::Relaxed);
+        Ok(())
+    }
+
+    pub async fn purge_all(&self) -> Result<()> {
+        self.purge_keys(&[ALL_PAGES_KEY.to_string()]).await
+    }
+
+    /// Surrogate keys of the pages that show a post, its listings, author
+    /// archive and terms. Take these before changing a post so pages it
+    /// is removed from are purged too.
+    pub async fn post_keys(&self, post_id: Uuid) -> Vec<String> {
+        let post: Option<(String, Uuid)> =
+            match sqlx::query_as("SELECT post_type, author_id FROM posts WHERE id = $1")
+                .bind(post_id)
+                .fetch_optional(&self.pool)
+                .await
+            {
+                Ok(post) => post,
+                Err(e) => {
+                    tracing::warn!(post_id = %post_id, "Failed to load post for purge: {}", e);
+                    None
+                }
+            };
+        let Some((post_type, author_id)) = post else {
+            return vec![format!("post:{}", post_id)];
+        };
+
+        let terms: Vec<(String, Uuid)> = sqlx::query_as(
+            r#"
+            SELECT tx.slug, t.id
+            FROM term_relationships tr
+            JOIN terms t ON t.id = tr.term_id
+            JOIN taxonomies tx ON tx.id = t.taxonomy_id
+            WHERE tr.object_id = $1 AND tr.object_type = 'post'
+            "#,
+        )
+        .bind(post_id)
+        .fetch_all(&self.pool)
+        .await
+        .unwrap_or_default();
+
+        let keys = super::SurrogateKeys::new()
+            .post(&post_id.to_string())
+            .post_type(&post_type)
+            .user(&author_id.to_string());
+        terms
+            .iter()
+            .fold(keys, |keys, (taxonomy, id)| {
+                keys.term(taxonomy, &id.to_string())
+            })
+            .build()
+    }
+
+    /// Purge the pages showing a post now, plus those from `before`
+    pub async fn purge_post(&self, post_id: Uuid, before: Vec<String>) {
+        let mut keys = before;
+        keys.extend(self.post_keys(post_id).await);
+        if let Err(e) = self.purge_keys(&keys).await {
+            tracing::warn!(post_id = %post_id, "Failed to purge cached pages: {}", e);
+        }
+    }
+
+    pub fn stats(&self) -> PageCacheStats {
+        PageCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            stores: self.stores.load(Ordering::Relaxed),
+            purges: self.purges.load(Ordering::Relaxed),
+        }
+    }
+}
+
+fn purge_marker(key: &str) -> CacheKey {
+    CacheKey::new(format!("{}{}", PURGED_PREFIX, key))
+}
+
+/// Cache tag of the pages rendered with a surrogate key
+fn page_tag(key: &str) -> String {
+    format!("{}{}", TAG_PREFIX, key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustpress_cache::MemoryBackend;
+
+    fn service() -> PageCacheService {
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .connect_lazy("postgres://localhost/rustpress_test")
+            .unwrap();
+        let cache = Cache::new(Arc::new(MemoryBackend::new(1000)));
+        PageCacheService::new(pool, Arc::new(cache))
+    }
+
+    #[test]
+    fn test_cache_key_normalisation() {
+        let config = PageCacheConfig::default();
+        let gzip = |name: &str| (name == "accept-encoding").then_some("gzip");
+
+        let a = config.cache_key("Example.com", "/hello", Some("b=2&a=1&utm_source=x"), gzip);
+        let b = config.cache_key("example.com", "/hello", Some("a=1&fbclid=y&b=2"), gzip);
+        assert_eq!(a, b);
+
+        let other_page = config.cache_key("example.com", "/hello", Some("a=1&b=3"), gzip);
+        let identity = config.cache_key("example.com", "/hello", Some("a=1&b=2"), |_| None);
+        assert_ne!(a, other_page);
+        assert_ne!(a, identity);
+
+        assert!(!config.has_bypass_cookie(["a=1"].into_iter()));
+        let config = PageCacheConfig {
+            bypass_cookies: vec!["comment_author".to_string()],
+            ..Default::default()
+        };
+        assert!(config.has_bypass_cookie(["x=1; comment_author=Jo"].into_iter()));
+    }
+
+    #[test]
+    fn test_shared_max_age() {
+        assert_eq!(
+            shared_max_age("public, max-age=60, s-maxage=600, stale-while-revalidate=60"),
+            Some(600)
+        );
+        assert_eq!(shared_max_age("public, max-age=300"), Some(300));
+        assert_eq!(shared_max_age("private, max-age=60"), None);
+        assert_eq!(shared_max_age("private, no-store"), None);
+
+        let invalid = PageCacheConfig {
+            ttl_secs: MAX_PAGE_TTL_SECS + 1,
+            ..Default::default()
+        };
+        assert!(invalid.validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_purge_by_surrogate_key() {
+        let service = service();
+        let key = PageCacheConfig::default().cache_key("example.com", "/hello", None, |_| None);
+        let page = CachedPage::new(
+            200,
+            [
+                ("content-type".to_string(), "text/html".to_string()),
+                ("x-request-id".to_string(), "abc".to_string()),
+            ],
+            b"<h1>Hello</h1>",
+            vec!["post:1".to_string(), "post_type:post".to_string()],
+            Utc::now().timestamp_millis() - 1,
+        );
+        assert_eq!(page.headers.len(), 1);
+        service.store(&key, &page, Duration::from_secs(60)).await;
+
+        let hit = service.lookup(&key).await.unwrap();
+        assert_eq!(hit.body(), b"<h1>Hello</h1>");
+
+        service.purge_keys(&["post:2".to_string()]).await.unwrap();
+        assert!(service.lookup(&key).await.is_some());
+
+        service.purge_keys(&["post:1".to_string()]).await.unwrap();
+        assert!(service.lookup(&key).await.is_none());
+        assert_eq!(service.stats().hits, 2);
+    }
+}
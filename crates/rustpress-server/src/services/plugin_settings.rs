@@ -0,0 +1,128 @@
+//! Plugin Settings Storage
+//!
+//! Keeps plugin settings in `plugin_settings`, one row per plugin and
+//! tenant, with an empty tenant ID for the installation-wide settings.
+//! Saves only succeed over the version they were based on, so concurrent
+//! edits surface as version conflicts.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rustpress_core::error::{Error, Result};
+use rustpress_core::plugin_settings::{PluginSettingsStore, StoredSettings};
+use serde_json::Value;
+use sqlx::types::Json;
+use sqlx::{FromRow, PgPool};
+
+#[derive(Debug, FromRow)]
+struct SettingsRow {
+    plugin_id: String,
+    tenant_id: String,
+    version: i64,
+    settings: Json<Value>,
+    updated_at: DateTime<Utc>,
+}
+
+impl From<SettingsRow> for StoredSettings {
+    fn from(row: SettingsRow) -> Self {
+        Self {
+            plugin_id: row.plugin_id,
+            tenant_id: Some(row.tenant_id).filter(|id| !id.is_empty()),
+            version: row.version,
+            values: row.settings.0,
+            updated_at: Some(row.updated_at),
+        }
+    }
+}
+
+/// Plugin settings kept in PostgreSQL
+pub struct PgPluginSettingsStore {
+    pool: PgPool,
+}
+
+impl PgPluginSettingsStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl PluginSettingsStore for PgPluginSettingsStore {
+    async fn load(
+        &self,
+        plugin_id: &str,
+        tenant_id: Option<&str>,
+    ) -> Result<Option<StoredSettings>> {
+        let row: Option<SettingsRow> = sqlx::query_as(
+            "SELECT plugin_id, tenant_id, version, settings, updated_at \
+             FROM plugin_settings WHERE plugin_id = $1 AND tenant_id = $2",
+        )
+        .bind(plugin_id)
+        .bind(tenant_id.unwrap_or_default())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load plugin settings", e))?;
+
+        Ok(row.map(StoredSettings::from))
+    }
+
+    async fn save(
+        &self,
+        plugin_id: &str,
+        tenant_id: Option<&str>,
+        values: Value,
+        expected_version: i64,
+    ) -> Result<StoredSettings> {
+        let tenant = tenant_id.unwrap_or_default();
+        let row: Option<SettingsRow> = if expected_version == 0 {
+            sqlx::query_as(
+                "INSERT INTO plugin_settings (plugin_id, tenant_id, version, settings) \
+                 VALUES ($1, $2, 1, $3) \
+                 ON CONFLICT (plugin_id, tenant_id) DO NOTHING \
+                 RETURNING plugin_id, tenant_id, version, settings, updated_at",
+            )
+            .bind(plugin_id)
+            .bind(tenant)
+            .bind(Json(&values))
+            .fetch_optional(&self.pool)
+            .await
+        } else {
+            sqlx::query_as(
+                "UPDATE plugin_settings \
+                 SET settings = $3, version = version + 1, updated_at = NOW() \
+                 WHERE plugin_id = $1 AND tenant_id = $2 AND version = $4 \
+                 RETURNING plugin_id, tenant_id, version, settings, updated_at",
+            )
+            .bind(plugin_id)
+            .bind(tenant)
+            .bind(Json(&values))
+            .bind(expected_version)
+            .fetch_optional(&self.pool)
+            .await
+        }
+        .map_err(|e| Error::database_with_source("Failed to save plugin settings", e))?;
+
+        match row {
+            Some(row) => Ok(row.into()),
+            None => {
+                let current = self
+                    .load(plugin_id, tenant_id)
+                    .await?
+                    .map_or(0, |settings| settings.version);
+                Err(Error::version_conflict(
+                    "PluginSettings",
+                    plugin_id,
+                    current,
+                ))
+            }
+        }
+    }
+
+    async fn delete(&self, plugin_id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM plugin_settings WHERE plugin_id = $1")
+            .bind(plugin_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to delete plugin settings", e))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,1156 @@
+//! Podcasting
+//!
+//! Posts become podcast episodes when an audio enclosure is attached:
+//!
+//! - an episode is a post of type `episode`, rendered, archived and
+//!   syndicated like any other post. Its enclosure (a media library upload
+//!   or an external URL), duration, season and episode number are kept in
+//!   `podcast_episodes`
+//! - the show itself (artwork, owner, iTunes categories, ...) is a setting;
+//!   `/podcast/feed` lists the episodes in RSS with the iTunes and Podcast
+//!   Index namespaces, and the site's other feeds carry enclosures too
+//! - enclosures are served from `/podcast/audio/{id}.{ext}`, with byte
+//!   ranges for uploads and a redirect for external files. Requests are
+//!   logged per listener and day, where a listener is a hash of address,
+//!   user agent and day that is never stored raw; as the IAB podcast
+//!   measurement guidelines suggest, a listener counts as a download once
+//!   a minute of audio was fetched
+
+use bytes::Bytes;
+use chrono::{DateTime, NaiveDate, Utc};
+use rustpress_core::error::{Error, Result};
+use rustpress_core::tenant::site_filter;
+use rustpress_storage::Storage;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::{FromRow, PgPool};
+use std::net::IpAddr;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use super::json_setting::JsonSetting;
+
+/// Settings key holding the podcast configuration
+pub const PODCAST_SETTINGS_KEY: &str = "podcast_config";
+
+/// Stored podcast settings
+const PODCAST_SETTING: JsonSetting<PodcastConfig> =
+    JsonSetting::new(PODCAST_SETTINGS_KEY, "podcast", "podcast settings");
+
+/// Post type of episodes
+pub const EPISODE_POST_TYPE: &str = "episode";
+
+/// Episodes listed in the podcast feed
+pub const PODCAST_FEED_SIZE: i32 = 300;
+
+/// Audio a listener must fetch for a download to count
+pub const COUNTED_SECONDS: i64 = 60;
+
+/// iTunes categories a show can be listed in
+const MAX_CATEGORIES: usize = 3;
+
+/// Longest show title, author or category
+const MAX_NAME_LENGTH: usize = 255;
+
+/// Longest show description, as Apple Podcasts accepts
+const MAX_DESCRIPTION_LENGTH: usize = 4_000;
+
+/// Days a download report may cover
+const MAX_REPORT_DAYS: i64 = 366;
+
+/// Episodes listed in a download report
+const REPORT_EPISODES: i64 = 50;
+
+pub(crate) fn is_web_url(url: &str) -> bool {
+    reqwest::Url::parse(url).is_ok_and(|url| matches!(url.scheme(), "http" | "https"))
+}
+
+/// Whether a show is a series of standalone episodes or meant to be heard
+/// in order
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ShowType {
+    #[default]
+    Episodic,
+    Serial,
+}
+
+impl ShowType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Episodic => "episodic",
+            Self::Serial => "serial",
+        }
+    }
+}
+
+/// Kind of an episode, as `itunes:episodeType` has it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EpisodeType {
+    #[default]
+    Full,
+    Trailer,
+    Bonus,
+}
+
+impl EpisodeType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Full => "full",
+            Self::Trailer => "trailer",
+            Self::Bonus => "bonus",
+        }
+    }
+}
+
+/// The show the episodes belong to
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PodcastConfig {
+    /// Show title; the site name when unset
+    pub title: Option<String>,
+    /// Show description; the site description when unset
+    pub description: Option<String>,
+    pub author: String,
+    /// Contact of the show's owner, shown to directories only
+    pub owner_name: String,
+    pub owner_email: String,
+    /// Square artwork of 1400 to 3000 pixels, absolute or site-relative
+    pub image_url: Option<String>,
+    /// iTunes categories, e.g. `Technology` or `Society & Culture > Documentary`
+    pub categories: Vec<String>,
+    pub explicit: bool,
+    pub show_type: ShowType,
+    /// Language of the episodes; the site's when unset
+    pub language: Option<String>,
+    pub copyright: Option<String>,
+    /// Ask other platforms not to import the feed (`podcast:locked`)
+    pub locked: bool,
+    pub funding_url: Option<String>,
+    pub funding_text: Option<String>,
+    /// Podcast Index GUID; assigned on first save and never changed
+    pub guid: Option<Uuid>,
+}
+
+impl PodcastConfig {
+    /// Load the configuration from settings, falling back to defaults
+    pub async fn load(pool: &PgPool) -> Result<Self> {
+        PODCAST_SETTING.load_or_default(pool).await
+    }
+
+    /// Persist the configuration to settings
+    pub async fn save(&self, pool: &PgPool) -> Result<()> {
+        PODCAST_SETTING.save(pool, self).await
+    }
+
+    /// Validate contact details, artwork, categories and links
+    pub fn validate(&self) -> Result<()> {
+        for (field, value) in [
+            ("title", self.title.as_deref().unwrap_or_default()),
+            ("author", &self.author),
+            ("owner_name", &self.owner_name),
+        ] {
+            if value.chars().count() > MAX_NAME_LENGTH {
+                return Err(Error::invalid_input(
+                    field,
+                    format!("Must be at most {} characters", MAX_NAME_LENGTH),
+                ));
+            }
+        }
+        if self
+            .description
+            .as_deref()
+            .unwrap_or_default()
+            .chars()
+            .count()
+            > MAX_DESCRIPTION_LENGTH
+        {
+            return Err(Error::invalid_input(
+                "description",
+                format!("Must be at most {} characters", MAX_DESCRIPTION_LENGTH),
+            ));
+        }
+        if !self.owner_email.is_empty() && !self.owner_email.contains('@') {
+            return Err(Error::invalid_input(
+                "owner_email",
+                "The owner email is not an email address",
+            ));
+        }
+        if let Some(image) = self.image_url.as_deref().filter(|u| !u.is_empty()) {
+            if !image.starts_with('/') && !is_web_url(image) {
+                return Err(Error::invalid_input(
+                    "image_url",
+                    "The artwork must be an http(s) or site-relative URL",
+                ));
+            }
+        }
+        if let Some(url) = self.funding_url.as_deref().filter(|u| !u.is_empty()) {
+            if !is_web_url(url) {
+                return Err(Error::invalid_input(
+                    "funding_url",
+                    "The funding link must be an http(s) URL",
+                ));
+            }
+        }
+
+        if self.categories.len() > MAX_CATEGORIES {
+            return Err(Error::invalid_input(
+                "categories",
+                format!("A show can be in at most {} categories", MAX_CATEGORIES),
+            ));
+        }
+        for category in &self.categories {
+            let valid = category.chars().count() <= MAX_NAME_LENGTH
+                && category.split('>').count() <= 2
+                && category.split('>').all(|part| !part.trim().is_empty());
+            if !valid {
+                return Err(Error::invalid_input(
+                    "categories",
+                    format!(
+                        "'{}' is not a category or 'Category > Subcategory'",
+                        category
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Prepare an update: the GUID identifies the show to directories, so
+    /// the stored one is kept
+    pub fn merge(&mut self, current: &PodcastConfig) {
+        self.guid = current.guid.or(self.guid).or_else(|| Some(Uuid::new_v4()));
+    }
+
+    /// iTunes categories split into category and subcategory
+    pub fn itunes_categories(&self) -> Vec<(&str, Option<&str>)> {
+        self.categories
+            .iter()
+            .map(|category| match category.split_once('>') {
+                Some((main, sub)) => (main.trim(), Some(sub.trim())),
+                None => (category.trim(), None),
+            })
+            .collect()
+    }
+}
+
+/// An episode's enclosure and numbering
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct Episode {
+    pub post_id: Uuid,
+    /// Uploaded audio in the media library
+    pub media_id: Option<Uuid>,
+    /// Audio hosted elsewhere
+    pub audio_url: Option<String>,
+    pub mime_type: String,
+    pub file_size: i64,
+    pub duration_seconds: i32,
+    pub season: Option<i32>,
+    pub episode_number: Option<i32>,
+    pub episode_type: String,
+    /// Overrides the show's explicit flag
+    pub explicit: Option<bool>,
+    pub transcript_url: Option<String>,
+    pub updated_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+const EPISODE_COLUMNS: &str = "post_id, media_id, audio_url, mime_type, file_size, \
+     duration_seconds, season, episode_number, episode_type, explicit, transcript_url, \
+     updated_by, created_at, updated_at";
+
+impl Episode {
+    /// Episode of a post, if it is one
+    pub async fn load(pool: &PgPool, post_id: Uuid) -> Result<Option<Self>> {
+        sqlx::query_as(&format!(
+            "SELECT {} FROM podcast_episodes WHERE post_id = $1 \
+             AND post_id IN (SELECT id FROM posts WHERE {})",
+            EPISODE_COLUMNS,
+            site_filter("site_id")
+        ))
+        .bind(post_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load episode", e))
+    }
+
+    /// Bytes a listener must fetch for a download to count: a minute of
+    /// audio, or the whole file when the episode is shorter or its length
+    /// is unknown
+    pub fn counted_bytes(&self) -> i64 {
+        if self.duration_seconds <= 0 {
+            return self.file_size;
+        }
+        let per_minute =
+            self.file_size.saturating_mul(COUNTED_SECONDS) / i64::from(self.duration_seconds);
+        per_minute.min(self.file_size)
+    }
+}
+
+/// An episode as templates and feeds see it, under `post.episode`
+#[derive(Debug, Clone, Serialize)]
+pub struct EpisodeData {
+    /// Site-relative URL the audio is downloaded from
+    pub url: String,
+    pub mime_type: String,
+    pub file_size: i64,
+    pub duration_seconds: i32,
+    /// `H:MM:SS`
+    pub duration: String,
+    pub season: Option<i32>,
+    pub episode_number: Option<i32>,
+    pub episode_type: String,
+    pub explicit: Option<bool>,
+    pub transcript_url: Option<String>,
+}
+
+impl From<&Episode> for EpisodeData {
+    fn from(episode: &Episode) -> Self {
+        Self {
+            url: audio_path(episode.post_id, &episode.mime_type),
+            mime_type: episode.mime_type.clone(),
+            file_size: episode.file_size,
+            duration_seconds: episode.duration_seconds,
+            duration: format_duration(episode.duration_seconds),
+            season: episode.season,
+            episode_number: episode.episode_number,
+            episode_type: episode.episode_type.clone(),
+            explicit: episode.explicit,
+            transcript_url: episode.transcript_url.clone(),
+        }
+    }
+}
+
+/// An episode with its post, for the admin
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct EpisodeSummary {
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    pub episode: Episode,
+    pub title: String,
+    pub slug: String,
+    pub status: String,
+    pub published_at: Option<DateTime<Utc>>,
+}
+
+/// Attach or replace a post's enclosure; give either `media_id` or
+/// `audio_url`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct EpisodeInput {
+    pub media_id: Option<Uuid>,
+    pub audio_url: Option<String>,
+    /// Needed with `audio_url`; taken from the upload otherwise
+    pub mime_type: Option<String>,
+    pub file_size: Option<i64>,
+    pub duration_seconds: i32,
+    pub season: Option<i32>,
+    pub episode_number: Option<i32>,
+    #[serde(default)]
+    pub episode_type: EpisodeType,
+    pub explicit: Option<bool>,
+    pub transcript_url: Option<String>,
+}
+
+impl EpisodeInput {
+    fn validate(&self) -> Result<()> {
+        match (self.media_id, self.audio_url.as_deref()) {
+            (Some(_), Some(_)) | (None, None) => {
+                return Err(Error::invalid_input(
+                    "audio",
+                    "Give either an uploaded file (media_id) or an audio_url",
+                ));
+            }
+            (None, Some(url)) => {
+                if !is_web_url(url) {
+                    return Err(Error::invalid_input(
+                        "audio_url",
+                        "The audio must be an http(s) URL",
+                    ));
+                }
+                if !self.mime_type.as_deref().is_some_and(is_audio_type) {
+                    return Err(Error::invalid_input(
+                        "mime_type",
+                        "External audio needs its audio or video MIME type",
+                    ));
+                }
+                if self.file_size.unwrap_or(0) < 0 {
+                    return Err(Error::invalid_input(
+                        "file_size",
+                        "The file size cannot be negative",
+                    ));
+                }
+            }
+            (Some(_), None) => {}
+        }
+        if self.duration_seconds <= 0 {
+            return Err(Error::invalid_input(
+                "duration_seconds",
+                "Episodes need their duration in seconds",
+            ));
+        }
+        if self.season.is_some_and(|n| n <= 0) || self.episode_number.is_some_and(|n| n <= 0) {
+            return Err(Error::invalid_input(
+                "episode_number",
+                "Season and episode numbers start at 1",
+            ));
+        }
+        if let Some(url) = self.transcript_url.as_deref().filter(|u| !u.is_empty()) {
+            if !url.starts_with('/') && !is_web_url(url) {
+                return Err(Error::invalid_input(
+                    "transcript_url",
+                    "The transcript must be an http(s) or site-relative URL",
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn is_audio_type(mime_type: &str) -> bool {
+    mime_type.starts_with("audio/") || mime_type.starts_with("video/")
+}
+
+/// Where an episode's audio comes from
+#[derive(Debug, Clone)]
+pub enum EpisodeAudio {
+    Stored { content_type: String, bytes: Bytes },
+    External(String),
+}
+
+/// Part of a file a request asks for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteRange {
+    Full,
+    /// Inclusive bounds
+    Partial {
+        start: u64,
+        end: u64,
+    },
+    Unsatisfiable,
+}
+
+impl ByteRange {
+    /// Parse a `Range` header for a file of `len` bytes. Only single
+    /// ranges are honoured; anything else is answered with the whole file,
+    /// as HTTP allows.
+    pub fn parse(header: Option<&str>, len: u64) -> Self {
+        let Some(spec) = header.and_then(|h| h.trim().strip_prefix("bytes=")) else {
+            return Self::Full;
+        };
+        if spec.contains(',') {
+            return Self::Full;
+        }
+        let Some((start, end)) = spec.trim().split_once('-') else {
+            return Self::Full;
+        };
+        let (start, end) = (start.trim(), end.trim());
+
+        let bounds = match (start.is_empty(), end.is_empty()) {
+            // Suffix: the last `end` bytes
+            (true, false) => match end.parse::<u64>() {
+                Ok(0) => return Self::Unsatisfiable,
+                Ok(suffix) => (len.saturating_sub(suffix), len.saturating_sub(1)),
+                Err(_) => return Self::Full,
+            },
+            (false, true) => match start.parse::<u64>() {
+                Ok(start) => (start, len.saturating_sub(1)),
+                Err(_) => return Self::Full,
+            },
+            (false, false) => match (start.parse::<u64>(), end.parse::<u64>()) {
+                (Ok(start), Ok(end)) if start <= end => (start, end.min(len.saturating_sub(1))),
+                _ => return Self::Full,
+            },
+            (true, true) => return Self::Full,
+        };
+        if len == 0 || bounds.0 >= len {
+            return Self::Unsatisfiable;
+        }
+        Self::Partial {
+            start: bounds.0,
+            end: bounds.1,
+        }
+    }
+}
+
+/// Path episode audio is downloaded from; the extension helps podcast
+/// apps that look at it
+pub fn audio_path(post_id: Uuid, mime_type: &str) -> String {
+    let extension = match mime_type {
+        "audio/mpeg" | "audio/mp3" => "mp3",
+        "audio/mp4" | "audio/x-m4a" | "audio/aac" => "m4a",
+        "audio/ogg" | "audio/opus" => "ogg",
+        "audio/wav" | "audio/x-wav" => "wav",
+        "audio/flac" => "flac",
+        "video/mp4" => "mp4",
+        "video/quicktime" => "mov",
+        _ => "bin",
+    };
+    format!("/podcast/audio/{}.{}", post_id, extension)
+}
+
+/// `H:MM:SS` or `M:SS`, as `itunes:duration` shows it
+pub fn format_duration(seconds: i32) -> String {
+    let seconds = seconds.max(0);
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
+
+/// Podcast app a request comes from, by user agent
+pub fn podcast_app(user_agent: &str) -> &'static str {
+    const APPS: [(&str, &str); 14] = [
+        ("Spotify", "Spotify"),
+        ("Overcast", "Overcast"),
+        ("PocketCasts", "Pocket Casts"),
+        ("Pocket Casts", "Pocket Casts"),
+        ("Castro", "Castro"),
+        ("PodcastAddict", "Podcast Addict"),
+        ("AntennaPod", "AntennaPod"),
+        ("Podbean", "Podbean"),
+        ("CastBox", "Castbox"),
+        ("Podverse", "Podverse"),
+        ("Fountain", "Fountain"),
+        ("Podcasts/", "Apple Podcasts"),
+        ("AppleCoreMedia", "Apple Podcasts"),
+        ("iTunes", "Apple Podcasts"),
+    ];
+    APPS.iter()
+        .find(|(marker, _)| user_agent.contains(marker))
+        .map(|(_, app)| *app)
+        .unwrap_or_else(|| {
+            if user_agent.starts_with("Mozilla/") {
+                "Browser"
+            } else {
+                "Other"
+            }
+        })
+}
+
+/// Anonymous listener of a day: a hash of address and user agent that
+/// changes daily
+pub fn listener_hash(ip: Option<IpAddr>, user_agent: &str, day: NaiveDate) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(ip.map(|ip| ip.to_string()).unwrap_or_default());
+    hasher.update([0]);
+    hasher.update(user_agent);
+    hasher.update([0]);
+    hasher.update(day.to_string());
+    format!("{:x}", hasher.finalize())
+}
+
+/// A request for an episode's audio
+#[derive(Debug, Clone)]
+pub struct DownloadRequest<'a> {
+    pub ip: Option<IpAddr>,
+    pub user_agent: &'a str,
+    /// Bytes sent in the response
+    pub bytes: i64,
+}
+
+/// Download report query
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DownloadQuery {
+    /// First day, 30 days ago by default
+    pub from: Option<NaiveDate>,
+    /// Last day, today by default
+    pub to: Option<NaiveDate>,
+    /// Only this episode
+    pub post_id: Option<Uuid>,
+}
+
+/// Downloads of one day
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct DailyDownloads {
+    pub day: NaiveDate,
+    pub downloads: i64,
+}
+
+/// Downloads of one episode
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct EpisodeDownloads {
+    pub post_id: Uuid,
+    pub title: String,
+    pub slug: String,
+    pub downloads: i64,
+    pub requests: i64,
+    pub bytes_served: i64,
+}
+
+/// Downloads from one podcast app
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct AppDownloads {
+    pub app: String,
+    pub downloads: i64,
+}
+
+/// Downloads over a period
+#[derive(Debug, Clone, Serialize)]
+pub struct DownloadReport {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub downloads: i64,
+    /// Audio requests, ranges and retries included
+    pub requests: i64,
+    pub bytes_served: i64,
+    pub daily: Vec<DailyDownloads>,
+    pub episodes: Vec<EpisodeDownloads>,
+    pub apps: Vec<AppDownloads>,
+}
+
+/// Episodes, show settings and download statistics
+pub struct PodcastService {
+    pool: PgPool,
+    storage: Arc<Storage>,
+    config: RwLock<Option<Arc<PodcastConfig>>>,
+}
+
+impl PodcastService {
+    pub fn new(pool: PgPool, storage: Arc<Storage>) -> Self {
+        Self {
+            pool,
+            storage,
+            config: RwLock::new(None),
+        }
+    }
+
+    /// Podcast configuration, loaded from settings on first use
+    pub async fn config(&self) -> Arc<PodcastConfig> {
+        if let Some(config) = self.config.read().await.clone() {
+            return config;
+        }
+
+        match PodcastConfig::load(&self.pool).await {
+            Ok(config) => {
+                let config = Arc::new(config);
+                *self.config.write().await = Some(config.clone());
+                config
+            }
+            Err(e) => {
+                tracing::warn!("Failed to load podcast settings, using defaults: {}", e);
+                Arc::new(PodcastConfig::default())
+            }
+        }
+    }
+
+    /// Validate and save a new configuration
+    pub async fn update_config(&self, mut config: PodcastConfig) -> Result<PodcastConfig> {
+        let current = self.config().await;
+        config.merge(&current);
+        config.validate()?;
+        config.save(&self.pool).await?;
+        *self.config.write().await = Some(Arc::new(config.clone()));
+        Ok(config)
+    }
+
+    /// Drop the cached configuration; the next use reloads it
+    pub async fn invalidate(&self) {
+        *self.config.write().await = None;
+    }
+
+    /// Episode of a post
+    pub async fn episode(&self, post_id: Uuid) -> Result<Episode> {
+        Episode::load(&self.pool, post_id)
+            .await?
+            .ok_or_else(|| Error::not_found("Episode", post_id.to_string()))
+    }
+
+    /// Episodes, newest first
+    pub async fn list(&self, page: u32, per_page: u32) -> Result<(Vec<EpisodeSummary>, u64)> {
+        let columns = EPISODE_COLUMNS
+            .split(", ")
+            .map(|column| format!("e.{}", column.trim()))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let episodes = sqlx::query_as(&format!(
+            "SELECT {}, p.title, p.slug, p.status, p.published_at \
+             FROM podcast_episodes e JOIN posts p ON p.id = e.post_id \
+             WHERE p.deleted_at IS NULL AND {} \
+             ORDER BY p.published_at DESC NULLS FIRST, e.created_at DESC \
+             LIMIT $1 OFFSET $2",
+            columns,
+            site_filter("p.site_id")
+        ))
+        .bind(i64::from(per_page))
+        .bind(i64::from(page.saturating_sub(1)) * i64::from(per_page))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to list episodes", e))?;
+
+        let total: i64 = sqlx::query_scalar(&format!(
+            "SELECT COUNT(*) FROM podcast_episodes e JOIN posts p ON p.id = e.post_id \
+             WHERE p.deleted_at IS NULL AND {}",
+            site_filter("p.site_id")
+        ))
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to count episodes", e))?;
+        Ok((episodes, total as u64))
+    }
+
+    /// Make a post an episode, or replace its enclosure
+    pub async fn save(&self, post_id: Uuid, user_id: Uuid, input: EpisodeInput) -> Result<Episode> {
+        input.validate()?;
+
+        let post_type: Option<String> = sqlx::query_scalar(&format!(
+            "SELECT post_type FROM posts WHERE id = $1 AND deleted_at IS NULL AND {}",
+            site_filter("site_id")
+        ))
+        .bind(post_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load post", e))?;
+        match post_type.as_deref() {
+            None => return Err(Error::not_found("Post", post_id.to_string())),
+            Some("post") | Some(EPISODE_POST_TYPE) => {}
+            Some(_) => {
+                return Err(Error::invalid_input(
+                    "post",
+                    "Only posts can be podcast episodes",
+                ));
+            }
+        }
+
+        let (mime_type, file_size) = match input.media_id {
+            Some(media_id) => {
+                let media: Option<(String, i64)> = sqlx::query_as(&format!(
+                    "SELECT mime_type, file_size FROM media \
+                     WHERE id = $1 AND deleted_at IS NULL AND {}",
+                    site_filter("site_id")
+                ))
+                .bind(media_id)
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(|e| Error::database_with_source("Failed to load media", e))?;
+                let (mime_type, file_size) =
+                    media.ok_or_else(|| Error::not_found("Media", media_id.to_string()))?;
+                if !is_audio_type(&mime_type) {
+                    return Err(Error::invalid_input(
+                        "media_id",
+                        format!("'{}' files cannot be episodes", mime_type),
+                    ));
+                }
+                (mime_type, file_size)
+            }
+            None => (
+                input.mime_type.clone().unwrap_or_default(),
+                input.file_size.unwrap_or(0),
+            ),
+        };
+
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| Error::database_with_source("Failed to start transaction", e))?;
+        let episode: Episode = sqlx::query_as(&format!(
+            "INSERT INTO podcast_episodes (post_id, media_id, audio_url, mime_type, file_size, \
+             duration_seconds, season, episode_number, episode_type, explicit, transcript_url, \
+             updated_by) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12) \
+             ON CONFLICT (post_id) DO UPDATE SET media_id = EXCLUDED.media_id, \
+             audio_url = EXCLUDED.audio_url, mime_type = EXCLUDED.mime_type, \
+             file_size = EXCLUDED.file_size, duration_seconds = EXCLUDED.duration_seconds, \
+             season = EXCLUDED.season, episode_number = EXCLUDED.episode_number, \
+             episode_type = EXCLUDED.episode_type, explicit = EXCLUDED.explicit, \
+             transcript_url = EXCLUDED.transcript_url, updated_by = EXCLUDED.updated_by, \
+             updated_at = NOW() \
+             RETURNING {}",
+            EPISODE_COLUMNS
+        ))
+        .bind(post_id)
+        .bind(input.media_id)
+        .bind(
+            input
+                .media_id
+                .is_none()
+                .then_some(input.audio_url)
+                .flatten(),
+        )
+        .bind(&mime_type)
+        .bind(file_size)
+        .bind(input.duration_seconds)
+        .bind(input.season)
+        .bind(input.episode_number)
+        .bind(input.episode_type.as_str())
+        .bind(input.explicit)
+        .bind(input.transcript_url.filter(|u| !u.is_empty()))
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to save episode", e))?;
+
+        sqlx::query("UPDATE posts SET post_type = $2, updated_at = NOW() WHERE id = $1")
+            .bind(post_id)
+            .bind(EPISODE_POST_TYPE)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to update post", e))?;
+        tx.commit()
+            .await
+            .map_err(|e| Error::database_with_source("Failed to save episode", e))?;
+        Ok(episode)
+    }
+
+    /// Turn an episode back into a plain post; its download log is kept
+    pub async fn remove(&self, post_id: Uuid) -> Result<()> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| Error::database_with_source("Failed to start transaction", e))?;
+        let removed = sqlx::query(&format!(
+            "DELETE FROM podcast_episodes WHERE post_id = $1 \
+             AND post_id IN (SELECT id FROM posts WHERE {})",
+            site_filter("site_id")
+        ))
+        .bind(post_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to remove episode", e))?
+        .rows_affected();
+        if removed == 0 {
+            return Err(Error::not_found("Episode", post_id.to_string()));
+        }
+        sqlx::query(
+            "UPDATE posts SET post_type = 'post', updated_at = NOW() \
+             WHERE id = $1 AND post_type = $2",
+        )
+        .bind(post_id)
+        .bind(EPISODE_POST_TYPE)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to update post", e))?;
+        tx.commit()
+            .await
+            .map_err(|e| Error::database_with_source("Failed to remove episode", e))
+    }
+
+    /// Episode of a published post, for downloads
+    pub async fn published(&self, post_id: Uuid) -> Result<Episode> {
+        let columns = EPISODE_COLUMNS
+            .split(", ")
+            .map(|column| format!("e.{}", column.trim()))
+            .collect::<Vec<_>>()
+            .join(", ");
+        sqlx::query_as(&format!(
+            "SELECT {} FROM podcast_episodes e JOIN posts p ON p.id = e.post_id \
+             WHERE e.post_id = $1 AND p.status = 'published' AND p.deleted_at IS NULL AND {}",
+            columns,
+            site_filter("p.site_id")
+        ))
+        .bind(post_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load episode", e))?
+        .ok_or_else(|| Error::not_found("Episode", post_id.to_string()))
+    }
+
+    /// The audio of an episode
+    pub async fn audio(&self, episode: &Episode) -> Result<EpisodeAudio> {
+        let Some(media_id) = episode.media_id else {
+            return match &episode.audio_url {
+                Some(url) => Ok(EpisodeAudio::External(url.clone())),
+                None => Err(Error::not_found(
+                    "Episode audio",
+                    episode.post_id.to_string(),
+                )),
+            };
+        };
+        let path: Option<String> = sqlx::query_scalar(&format!(
+            "SELECT storage_path FROM media WHERE id = $1 AND deleted_at IS NULL AND {}",
+            site_filter("site_id")
+        ))
+        .bind(media_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load media", e))?
+        .flatten();
+        let path = path.ok_or_else(|| Error::not_found("Media", media_id.to_string()))?;
+        Ok(EpisodeAudio::Stored {
+            content_type: episode.mime_type.clone(),
+            bytes: self.storage.get(&path).await?,
+        })
+    }
+
+    /// Log a request for an episode's audio
+    pub async fn record(&self, episode: &Episode, request: DownloadRequest<'_>) -> Result<()> {
+        let day = Utc::now().date_naive();
+        sqlx::query(
+            "INSERT INTO podcast_downloads (post_id, day, listener_hash, app, bytes_served, counted) \
+             VALUES ($1, $2, $3, $4, $5, $5 >= $6) \
+             ON CONFLICT (post_id, day, listener_hash) DO UPDATE SET \
+             requests = podcast_downloads.requests + 1, \
+             bytes_served = podcast_downloads.bytes_served + EXCLUDED.bytes_served, \
+             counted = podcast_downloads.bytes_served + EXCLUDED.bytes_served >= $6, \
+             last_at = NOW()",
+        )
+        .bind(episode.post_id)
+        .bind(day)
+        .bind(listener_hash(request.ip, request.user_agent, day))
+        .bind(podcast_app(request.user_agent))
+        .bind(request.bytes)
+        .bind(episode.counted_bytes())
+        .execute(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to record download", e))?;
+        Ok(())
+    }
+
+    /// Downloads per day, episode and app over a period
+    pub async fn report(&self, query: &DownloadQuery) -> Result<DownloadReport> {
+        let to = query.to.unwrap_or_else(|| Utc::now().date_naive());
+        let from = query.from.unwrap_or(to - chrono::Duration::days(29));
+        if from > to || (to - from).num_days() >= MAX_REPORT_DAYS {
+            return Err(Error::invalid_input(
+                "from",
+                format!(
+                    "Reports cover 1 to {} days, starting before they end",
+                    MAX_REPORT_DAYS
+                ),
+            ));
+        }
+
+        let posts = format!("SELECT id FROM posts WHERE {}", site_filter("site_id"));
+        let filter = format!(
+            "d.day BETWEEN $1 AND $2 AND ($3::uuid IS NULL OR d.post_id = $3) \
+             AND d.post_id IN ({})",
+            posts
+        );
+        let db = |e| Error::database_with_source("Failed to build download report", e);
+
+        let (downloads, requests, bytes_served): (i64, i64, i64) = sqlx::query_as(&format!(
+            "SELECT COUNT(*) FILTER (WHERE d.counted), COALESCE(SUM(d.requests), 0)::bigint, \
+             COALESCE(SUM(d.bytes_served), 0)::bigint FROM podcast_downloads d WHERE {}",
+            filter
+        ))
+        .bind(from)
+        .bind(to)
+        .bind(query.post_id)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(db)?;
+
+        let daily = sqlx::query_as(&format!(
+            "SELECT days.day::date AS day, COUNT(d.post_id) AS downloads \
+             FROM generate_series($1::date, $2::date, INTERVAL '1 day') AS days(day) \
+             LEFT JOIN podcast_downloads d ON d.day = days.day::date AND d.counted \
+             AND ($3::uuid IS NULL OR d.post_id = $3) AND d.post_id IN ({}) \
+             GROUP BY days.day ORDER BY days.day",
+            posts
+        ))
+        .bind(from)
+        .bind(to)
+        .bind(query.post_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(db)?;
+
+        let episodes = sqlx::query_as(&format!(
+            "SELECT d.post_id, p.title, p.slug, COUNT(*) FILTER (WHERE d.counted) AS downloads, \
+             SUM(d.requests)::bigint AS requests, SUM(d.bytes_served)::bigint AS bytes_served \
+             FROM podcast_downloads d JOIN posts p ON p.id = d.post_id WHERE {} \
+             GROUP BY d.post_id, p.title, p.slug ORDER BY downloads DESC, requests DESC LIMIT {}",
+            filter, REPORT_EPISODES
+        ))
+        .bind(from)
+        .bind(to)
+        .bind(query.post_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(db)?;
+
+        let apps = sqlx::query_as(&format!(
+            "SELECT d.app, COUNT(*) AS downloads FROM podcast_downloads d \
+             WHERE {} AND d.counted GROUP BY d.app ORDER BY downloads DESC, d.app",
+            filter
+        ))
+        .bind(from)
+        .bind(to)
+        .bind(query.post_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(db)?;
+
+        Ok(DownloadReport {
+            from,
+            to,
+            downloads,
+            requests,
+            bytes_served,
+            daily,
+            episodes,
+            apps,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_byte_ranges() {
+        assert_eq!(ByteRange::parse(None, 100), ByteRange::Full);
+        assert_eq!(
+            ByteRange::parse(Some("bytes=0-1"), 100),
+            ByteRange::Partial { start: 0, end: 1 }
+        );
+        assert_eq!(
+            ByteRange::parse(Some("bytes=90-"), 100),
+            ByteRange::Partial { start: 90, end: 99 }
+        );
+        assert_eq!(
+            ByteRange::parse(Some("bytes=-10"), 100),
+            ByteRange::Partial { start: 90, end: 99 }
+        );
+        assert_eq!(
+            ByteRange::parse(Some("bytes=50-500"), 100),
+            ByteRange::Partial { start: 50, end: 99 }
+        );
+        assert_eq!(
+            ByteRange::parse(Some("bytes=100-"), 100),
+            ByteRange::Unsatisfiable
+        );
+        assert_eq!(
+            ByteRange::parse(Some("bytes=-0"), 100),
+            ByteRange::Unsatisfiable
+        );
+        assert_eq!(
+            ByteRange::parse(Some("bytes=0-1,5-9"), 100),
+            ByteRange::Full
+        );
+        assert_eq!(ByteRange::parse(Some("bytes=9-5"), 100), ByteRange::Full);
+        assert_eq!(ByteRange::parse(Some("items=0-1"), 100), ByteRange::Full);
+    }
+
+    #[test]
+    fn test_episode_helpers() {
+        let id = Uuid::nil();
+        assert_eq!(
+            audio_path(id, "audio/mpeg"),
+            "/podcast/audio/00000000-0000-0000-0000-000000000000.mp3"
+        );
+        assert!(audio_path(id, "audio/x-m4a").ends_with(".m4a"));
+        assert_eq!(format_duration(59), "0:59");
+        assert_eq!(format_duration(3725), "1:02:05");
+
+        assert_eq!(
+            podcast_app("AppleCoreMedia/1.0.0.20G165 (iPhone; U; CPU OS 16_6)"),
+            "Apple Podcasts"
+        );
+        assert_eq!(
+            podcast_app("Podcasts/1650.20 CFNetwork/1408.0.4 Darwin/22.5.0"),
+            "Apple Podcasts"
+        );
+        assert_eq!(
+            podcast_app("Spotify/8.8.0 iOS/16.6 (iPhone15,2)"),
+            "Spotify"
+        );
+        assert_eq!(
+            podcast_app("PocketCasts/1.0 (Pocket Casts Feed Parser)"),
+            "Pocket Casts"
+        );
+        assert_eq!(
+            podcast_app("Mozilla/5.0 (X11; Linux x86_64) Firefox/118.0"),
+            "Browser"
+        );
+        assert_eq!(podcast_app("curl/8.1"), "Other");
+
+        let day = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let ip = "192.0.2.1".parse().ok();
+        let hash = listener_hash(ip, "Overcast/3.0", day);
+        assert_eq!(hash.len(), 64);
+        assert_eq!(hash, listener_hash(ip, "Overcast/3.0", day));
+        assert_ne!(
+            hash,
+            listener_hash(ip, "Overcast/3.0", day.succ_opt().unwrap())
+        );
+
+        let mut episode = Episode {
+            post_id: id,
+            media_id: None,
+            audio_url: Some("https://cdn.example.com/1.mp3".to_string()),
+            mime_type: "audio/mpeg".to_string(),
+            file_size: 60_000_000,
+            duration_seconds: 3600,
+            season: None,
+            episode_number: Some(1),
+            episode_type: "full".to_string(),
+            explicit: None,
+            transcript_url: None,
+            updated_by: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        assert_eq!(episode.counted_bytes(), 1_000_000);
+        episode.duration_seconds = 30;
+        assert_eq!(episode.counted_bytes(), 60_000_000);
+        episode.duration_seconds = 0;
+        assert_eq!(episode.counted_bytes(), 60_000_000);
+        assert_eq!(EpisodeData::from(&episode).duration, "0:00");
+    }
+
+    #[test]
+    fn test_config_and_input_validation() {
+        let mut config = PodcastConfig {
+            owner_email: "host@example.com".to_string(),
+            categories: vec![
+                "Technology".to_string(),
+                "Society & Culture > Documentary".to_string(),
+            ],
+            image_url: Some("/uploads/cover.jpg".to_string()),
+            ..Default::default()
+        };
+        config.validate().unwrap();
+        assert_eq!(
+            config.itunes_categories(),
+            vec![
+                ("Technology", None),
+                ("Society & Culture", Some("Documentary"))
+            ]
+        );
+
+        config.categories.push("A > B > C".to_string());
+        assert!(config.validate().is_err());
+        config.categories.pop();
+        config.owner_email = "nobody".to_string();
+        assert!(config.validate().is_err());
+        config.owner_email.clear();
+        config.funding_url = Some("ftp://example.com".to_string());
+        assert!(config.validate().is_err());
+        config.funding_url = None;
+
+        // The GUID is assigned once and kept
+        config.merge(&PodcastConfig::default());
+        let guid = config.guid;
+        assert!(guid.is_some());
+        let mut update = PodcastConfig::default();
+        update.merge(&config);
+        assert_eq!(update.guid, guid);
+
+        let mut input = EpisodeInput {
+            audio_url: Some("https://cdn.example.com/1.mp3".to_string()),
+            mime_type: Some("audio/mpeg".to_string()),
+            duration_seconds: 1800,
+            ..Default::default()
+        };
+        input.validate().unwrap();
+        input.media_id = Some(Uuid::nil());
+        assert!(input.validate().is_err());
+        input.media_id = None;
+        input.mime_type = Some("image/png".to_string());
+        assert!(input.validate().is_err());
+        input.mime_type = Some("audio/mpeg".to_string());
+        input.episode_number = Some(0);
+        assert!(input.validate().is_err());
+        input.episode_number = Some(1);
+        input.duration_seconds = 0;
+        assert!(input.validate().is_err());
+    }
+}
@@ -0,0 +1,959 @@
+//! Public API
+//!
+//! Read-only API tier under [`PUBLIC_API_PREFIX`] for third-party sites and
+//! apps. It is kept apart from the authenticated admin API:
+//!
+//! - requests carry a public API key (`X-API-Key` header or `api_key` query
+//!   parameter) rather than a user session, and keys can only read published
+//!   content
+//! - every key has its own per-minute limit and optional daily quota
+//! - successful responses are shared between keys through the cache for
+//!   [`PUBLIC_API_CACHE_TTL`] and marked publicly cacheable
+//! - usage is counted per key in memory and flushed into hourly and
+//!   per-endpoint daily rollup tables, which the admin usage report reads
+//!
+//! Only the SHA-256 hash of a key is stored; the secret is shown once, when
+//! the key is created.
+
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use base64::Engine as _;
+use chrono::{DateTime, DurationRound, NaiveDate, TimeDelta, Utc};
+use parking_lot::Mutex;
+use rustpress_cache::Cache;
+use rustpress_core::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::types::Json;
+use sqlx::{FromRow, PgPool};
+use std::collections::HashMap;
+use std::mem;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+use super::page_cache::CachedPage;
+
+/// Path the public API is served under
+pub const PUBLIC_API_PREFIX: &str = "/api/public/v1";
+
+/// Prefix of every public API key, so leaked keys are easy to recognise
+pub const PUBLIC_API_KEY_PREFIX: &str = "rpk_";
+
+/// Per-minute limit of keys created without one
+pub const DEFAULT_REQUESTS_PER_MINUTE: u32 = 60;
+
+/// Highest per-minute limit a key can be given
+pub const MAX_REQUESTS_PER_MINUTE: u32 = 1200;
+
+/// How long successful responses are shared between keys
+pub const PUBLIC_API_CACHE_TTL: Duration = Duration::from_secs(300);
+
+/// How often buffered usage is written to the rollup tables
+pub const USAGE_FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Longest period the usage report covers
+pub const MAX_REPORT_DAYS: u32 = 90;
+
+/// Longest accepted key name
+const MAX_KEY_NAME_LENGTH: usize = 100;
+
+/// Most origins a key can be restricted to
+const MAX_ALLOWED_ORIGINS: usize = 20;
+
+/// Random bytes in a key, 40 characters once encoded
+const KEY_SECRET_BYTES: usize = 30;
+
+/// Characters of the key kept in clear for display
+const KEY_DISPLAY_LENGTH: usize = 12;
+
+/// How long a key lookup, including a failed one, is reused
+const KEY_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// Cached key lookups kept before expired ones are dropped
+const KEY_CACHE_CAPACITY: usize = 4096;
+
+/// Responses larger than this are not cached
+const MAX_CACHED_BODY_BYTES: usize = 512 * 1024;
+
+const KEY_COLUMNS: &str = "id, name, key_prefix, owner_id, requests_per_minute, daily_quota, \
+     allowed_origins, last_used_at, revoked_at, created_at, updated_at";
+
+/// A public API key, without its secret
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct PublicApiKey {
+    pub id: Uuid,
+    pub name: String,
+    /// Start of the key, for telling keys apart
+    pub key_prefix: String,
+    pub owner_id: Option<Uuid>,
+    pub requests_per_minute: i32,
+    /// Requests allowed per UTC day; unlimited when absent
+    pub daily_quota: Option<i32>,
+    /// Browser origins allowed to use the key; any when empty. Requests
+    /// without an `Origin` header are always allowed.
+    pub allowed_origins: Json<Vec<String>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl PublicApiKey {
+    /// Whether a browser origin may use the key
+    pub fn allows_origin(&self, origin: Option<&str>) -> bool {
+        match origin {
+            None => true,
+            Some(origin) => {
+                self.allowed_origins.is_empty()
+                    || self
+                        .allowed_origins
+                        .iter()
+                        .any(|allowed| allowed.eq_ignore_ascii_case(origin))
+            }
+        }
+    }
+}
+
+/// A newly created key; `secret` is never shown again
+#[derive(Debug, Clone, Serialize)]
+pub struct CreatedPublicApiKey {
+    #[serde(flatten)]
+    pub key: PublicApiKey,
+    pub secret: String,
+}
+
+/// Name, limits and origins of a key as submitted
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PublicApiKeyInput {
+    pub name: String,
+    #[serde(default)]
+    pub requests_per_minute: Option<u32>,
+    #[serde(default)]
+    pub daily_quota: Option<u32>,
+    #[serde(default)]
+    pub allowed_origins: Vec<String>,
+}
+
+impl PublicApiKeyInput {
+    fn normalize(mut self) -> Result<Self> {
+        self.name = self.name.trim().to_string();
+        if self.name.is_empty() {
+            return Err(Error::invalid_input("name", "Name is required"));
+        }
+        if self.name.chars().count() > MAX_KEY_NAME_LENGTH {
+            return Err(Error::invalid_input(
+                "name",
+                format!("Must be at most {} characters", MAX_KEY_NAME_LENGTH),
+            ));
+        }
+
+        let requests_per_minute = self
+            .requests_per_minute
+            .unwrap_or(DEFAULT_REQUESTS_PER_MINUTE);
+        if requests_per_minute == 0 || requests_per_minute > MAX_REQUESTS_PER_MINUTE {
+            return Err(Error::invalid_input(
+                "requests_per_minute",
+                format!("Must be between 1 and {}", MAX_REQUESTS_PER_MINUTE),
+            ));
+        }
+        self.requests_per_minute = Some(requests_per_minute);
+
+        if let Some(quota) = self.daily_quota {
+            if quota == 0 || quota > i32::MAX as u32 {
+                return Err(Error::invalid_input(
+                    "daily_quota",
+                    "Must be a positive number of requests",
+                ));
+            }
+        }
+
+        if self.allowed_origins.len() > MAX_ALLOWED_ORIGINS {
+            return Err(Error::invalid_input(
+                "allowed_origins",
+                format!("At most {} origins can be listed", MAX_ALLOWED_ORIGINS),
+            ));
+        }
+        let mut origins: Vec<String> = Vec::with_capacity(self.allowed_origins.len());
+        for origin in &self.allowed_origins {
+            let origin = origin.trim().trim_end_matches('/').to_ascii_lowercase();
+            if !is_origin(&origin) {
+                return Err(Error::invalid_input(
+                    "allowed_origins",
+                    format!("'{}' is not an origin like https://example.com", origin),
+                ));
+            }
+            if !origins.contains(&origin) {
+                origins.push(origin);
+            }
+        }
+        self.allowed_origins = origins;
+
+        Ok(self)
+    }
+}
+
+/// Outcome of counting a request against a key's limits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateStatus {
+    /// Per-minute limit of the key
+    pub limit: u32,
+    /// Requests left in the current minute
+    pub remaining: u32,
+    /// Set when the request is over the minute limit or the daily quota
+    pub retry_after_secs: Option<u64>,
+}
+
+/// How a public API request ended, for usage accounting
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestOutcome {
+    /// Answered, with the response status
+    Served { status: u16, cache_hit: bool },
+    /// Refused by the key's limits
+    RateLimited,
+}
+
+/// Usage counters for one key and hour
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, FromRow)]
+pub struct UsageCounters {
+    pub requests: i64,
+    /// Response body bytes, before compression
+    pub bytes_out: i64,
+    /// Responses with a 4xx or 5xx status
+    pub errors: i64,
+    pub rate_limited: i64,
+    pub cache_hits: i64,
+}
+
+impl UsageCounters {
+    fn add(&mut self, other: &UsageCounters) {
+        self.requests += other.requests;
+        self.bytes_out += other.bytes_out;
+        self.errors += other.errors;
+        self.rate_limited += other.rate_limited;
+        self.cache_hits += other.cache_hits;
+    }
+}
+
+/// Counters not yet written to the rollup tables
+#[derive(Debug, Default)]
+struct UsageBuffer {
+    hourly: HashMap<(Uuid, DateTime<Utc>), UsageCounters>,
+    /// Requests and bytes per key, day and endpoint
+    endpoints: HashMap<(Uuid, NaiveDate, String), (i64, i64)>,
+    last_used: HashMap<Uuid, DateTime<Utc>>,
+}
+
+impl UsageBuffer {
+    fn is_empty(&self) -> bool {
+        self.hourly.is_empty() && self.endpoints.is_empty()
+    }
+
+    fn record(
+        &mut self,
+        key_id: Uuid,
+        endpoint: &str,
+        outcome: RequestOutcome,
+        bytes: u64,
+        at: DateTime<Utc>,
+    ) {
+        let hour = at.duration_trunc(TimeDelta::hours(1)).unwrap_or(at);
+        let bytes = bytes.min(i64::MAX as u64) as i64;
+        let counters = self.hourly.entry((key_id, hour)).or_default();
+        counters.requests += 1;
+        counters.bytes_out += bytes;
+        match outcome {
+            RequestOutcome::Served { status, cache_hit } => {
+                if status >= 400 {
+                    counters.errors += 1;
+                }
+                if cache_hit {
+                    counters.cache_hits += 1;
+                }
+            }
+            RequestOutcome::RateLimited => counters.rate_limited += 1,
+        }
+
+        let endpoint = self
+            .endpoints
+            .entry((key_id, at.date_naive(), endpoint.to_string()))
+            .or_default();
+        endpoint.0 += 1;
+        endpoint.1 += bytes;
+
+        let last_used = self.last_used.entry(key_id).or_insert(at);
+        *last_used = (*last_used).max(at);
+    }
+
+    /// Fold counters from a failed flush back in
+    fn merge(&mut self, other: UsageBuffer) {
+        for (key, counters) in other.hourly {
+            self.hourly.entry(key).or_default().add(&counters);
+        }
+        for (key, (requests, bytes)) in other.endpoints {
+            let entry = self.endpoints.entry(key).or_default();
+            entry.0 += requests;
+            entry.1 += bytes;
+        }
+        for (key_id, at) in other.last_used {
+            let last_used = self.last_used.entry(key_id).or_insert(at);
+            *last_used = (*last_used).max(at);
+        }
+    }
+}
+
+/// Usage of one key over the report period
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct KeyUsage {
+    pub key_id: Uuid,
+    pub name: String,
+    pub key_prefix: String,
+    pub revoked: bool,
+    pub last_used_at: Option<DateTime<Utc>>,
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    pub usage: UsageCounters,
+}
+
+/// Usage on one UTC day
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct DailyUsage {
+    pub day: NaiveDate,
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    pub usage: UsageCounters,
+}
+
+/// Usage of one endpoint over the report period
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct EndpointUsage {
+    pub endpoint: String,
+    pub requests: i64,
+    pub bytes_out: i64,
+}
+
+/// Public API usage for the admin dashboard
+#[derive(Debug, Clone, Serialize)]
+pub struct PublicApiUsageReport {
+    /// First day covered, inclusive
+    pub since: NaiveDate,
+    pub totals: UsageCounters,
+    pub keys: Vec<KeyUsage>,
+    pub daily: Vec<DailyUsage>,
+    pub top_endpoints: Vec<EndpointUsage>,
+}
+
+/// Key lookup cached at an instant; `None` remembers unknown or revoked keys
+type CachedKeyLookup = (Instant, Option<Arc<PublicApiKey>>);
+
+/// Keys, limits, response cache and usage of the public API tier
+pub struct PublicApiService {
+    pool: PgPool,
+    cache: Arc<Cache>,
+    /// Key lookups by hash
+    keys: Mutex<HashMap<String, CachedKeyLookup>>,
+    usage: Mutex<UsageBuffer>,
+}
+
+impl PublicApiService {
+    pub fn new(pool: PgPool, cache: Arc<Cache>) -> Self {
+        Self {
+            pool,
+            cache,
+            keys: Mutex::new(HashMap::new()),
+            usage: Mutex::new(UsageBuffer::default()),
+        }
+    }
+
+    /// All keys, newest first
+    pub async fn list_keys(&self) -> Result<Vec<PublicApiKey>> {
+        sqlx::query_as(&format!(
+            "SELECT {} FROM public_api_keys ORDER BY created_at DESC",
+            KEY_COLUMNS
+        ))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to list public API keys", e))
+    }
+
+    pub async fn get_key(&self, id: Uuid) -> Result<PublicApiKey> {
+        sqlx::query_as(&format!(
+            "SELECT {} FROM public_api_keys WHERE id = $1",
+            KEY_COLUMNS
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load public API key", e))?
+        .ok_or_else(|| Error::not_found("Public API key", id.to_string()))
+    }
+
+    /// Create a key; the returned secret is the only copy
+    pub async fn create_key(
+        &self,
+        owner_id: Uuid,
+        input: PublicApiKeyInput,
+    ) -> Result<CreatedPublicApiKey> {
+        let input = input.normalize()?;
+        let secret = generate_secret();
+
+        let key: PublicApiKey = sqlx::query_as(&format!(
+            r#"
+            INSERT INTO public_api_keys
+                (name, key_prefix, key_hash, owner_id, requests_per_minute, daily_quota,
+                 allowed_origins)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING {}
+            "#,
+            KEY_COLUMNS
+        ))
+        .bind(&input.name)
+        .bind(&secret[..KEY_DISPLAY_LENGTH])
+        .bind(hash_secret(&secret))
+        .bind(owner_id)
+        .bind(input.requests_per_minute.map(|n| n as i32))
+        .bind(input.daily_quota.map(|n| n as i32))
+        .bind(Json(&input.allowed_origins))
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to create public API key", e))?;
+
+        Ok(CreatedPublicApiKey { key, secret })
+    }
+
+    /// Change a key's name, limits and origins
+    pub async fn update_key(&self, id: Uuid, input: PublicApiKeyInput) -> Result<PublicApiKey> {
+        let input = input.normalize()?;
+        let key: PublicApiKey = sqlx::query_as(&format!(
+            r#"
+            UPDATE public_api_keys
+            SET name = $2, requests_per_minute = $3, daily_quota = $4, allowed_origins = $5,
+                updated_at = NOW()
+            WHERE id = $1
+            RETURNING {}
+            "#,
+            KEY_COLUMNS
+        ))
+        .bind(id)
+        .bind(&input.name)
+        .bind(input.requests_per_minute.map(|n| n as i32))
+        .bind(input.daily_quota.map(|n| n as i32))
+        .bind(Json(&input.allowed_origins))
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to update public API key", e))?
+        .ok_or_else(|| Error::not_found("Public API key", id.to_string()))?;
+
+        self.forget_key(id);
+        Ok(key)
+    }
+
+    /// Revoke a key; its usage history is kept
+    pub async fn revoke_key(&self, id: Uuid) -> Result<PublicApiKey> {
+        let key: PublicApiKey = sqlx::query_as(&format!(
+            r#"
+            UPDATE public_api_keys
+            SET revoked_at = COALESCE(revoked_at, NOW()), updated_at = NOW()
+            WHERE id = $1
+            RETURNING {}
+            "#,
+            KEY_COLUMNS
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to revoke public API key", e))?
+        .ok_or_else(|| Error::not_found("Public API key", id.to_string()))?;
+
+        self.forget_key(id);
+        Ok(key)
+    }
+
+    /// Drop cached lookups of a key so changes apply to the next request
+    fn forget_key(&self, id: Uuid) {
+        self.keys
+            .lock()
+            .retain(|_, (_, key)| key.as_ref().is_none_or(|key| key.id != id));
+    }
+
+    /// The active key a secret belongs to
+    pub async fn authenticate(&self, secret: &str) -> Result<Option<Arc<PublicApiKey>>> {
+        if !secret.starts_with(PUBLIC_API_KEY_PREFIX) {
+            return Ok(None);
+        }
+        let hash = hash_secret(secret);
+        if let Some((fetched_at, key)) = self.keys.lock().get(&hash) {
+            if fetched_at.elapsed() < KEY_CACHE_TTL {
+                return Ok(key.clone());
+            }
+        }
+
+        let key: Option<PublicApiKey> = sqlx::query_as(&format!(
+            "SELECT {} FROM public_api_keys WHERE key_hash = $1 AND revoked_at IS NULL",
+            KEY_COLUMNS
+        ))
+        .bind(&hash)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to look up public API key", e))?;
+        let key = key.map(Arc::new);
+
+        let mut keys = self.keys.lock();
+        if keys.len() >= KEY_CACHE_CAPACITY {
+            keys.retain(|_, (fetched_at, _)| fetched_at.elapsed() < KEY_CACHE_TTL);
+            if keys.len() >= KEY_CACHE_CAPACITY {
+                keys.clear();
+            }
+        }
+        keys.insert(hash, (Instant::now(), key.clone()));
+        Ok(key)
+    }
+
+    /// Count a request against the key's minute limit and daily quota.
+    /// Counters live in the shared cache so limits hold across instances;
+    /// when the cache is unavailable requests are let through.
+    pub async fn check_rate(&self, key: &PublicApiKey) -> RateStatus {
+        let limit = key.requests_per_minute.max(1) as u32;
+        let used = self
+            .cache
+            .increment_window(
+                format!("public_api_rate:{}", key.id),
+                1,
+                Duration::from_secs(60),
+            )
+            .await
+            .unwrap_or(0)
+            .max(0) as u64;
+        let mut status = RateStatus {
+            limit,
+            remaining: (limit as u64).saturating_sub(used) as u32,
+            retry_after_secs: None,
+        };
+        if used > limit as u64 {
+            status.retry_after_secs = Some(60);
+            return status;
+        }
+
+        if let Some(quota) = key.daily_quota {
+            let now = Utc::now();
+            let until_midnight = seconds_until_midnight(now);
+            let used_today = self
+                .cache
+                .increment_window(
+                    format!("public_api_quota:{}:{}", key.id, now.date_naive()),
+                    1,
+                    Duration::from_secs(until_midnight),
+                )
+                .await
+                .unwrap_or(0);
+            if used_today > quota as i64 {
+                status.retry_after_secs = Some(until_midnight);
+            }
+        }
+
+        status
+    }
+
+    /// A shared cached response
+    pub async fn cached_response(&self, cache_key: &str) -> Option<CachedPage> {
+        self.cache.get(cache_key).await.ok().flatten()
+    }
+
+    /// Share a successful response with every key for the cache TTL
+    pub async fn store_response(&self, cache_key: &str, page: &CachedPage, body_len: usize) {
+        if body_len > MAX_CACHED_BODY_BYTES {
+            return;
+        }
+        if let Err(e) = self
+            .cache
+            .set(cache_key, page, Some(PUBLIC_API_CACHE_TTL))
+            .await
+        {
+            tracing::warn!("Failed to cache public API response: {}", e);
+        }
+    }
+
+    /// Count a request in the usage buffer; `endpoint` is the route
+    /// template, so ids and slugs do not each get their own row
+    pub fn record(&self, key_id: Uuid, endpoint: &str, outcome: RequestOutcome, bytes: u64) {
+        self.usage
+            .lock()
+            .record(key_id, endpoint, outcome, bytes, Utc::now());
+    }
+
+    /// Write buffered usage to the rollup tables. Counters are put back
+    /// when the write fails, so the next flush retries them.
+    pub async fn flush_usage(&self) -> Result<()> {
+        let buffer = mem::take(&mut *self.usage.lock());
+        if buffer.is_empty() {
+            return Ok(());
+        }
+
+        let written = self.write_usage(&buffer).await;
+        if written.is_err() {
+            self.usage.lock().merge(buffer);
+        }
+        written
+    }
+
+    async fn write_usage(&self, buffer: &UsageBuffer) -> Result<()> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| Error::database_with_source("Failed to start usage flush", e))?;
+
+        for ((key_id, hour), counters) in &buffer.hourly {
+            sqlx::query(
+                r#"
+                INSERT INTO public_api_usage_hourly
+                    (key_id, hour, requests, bytes_out, errors, rate_limited, cache_hits)
+                VALUES ($1, $2, $3, $4, $5, $6, $7)
+                ON CONFLICT (key_id, hour) DO UPDATE SET
+                    requests = public_api_usage_hourly.requests + EXCLUDED.requests,
+                    bytes_out = public_api_usage_hourly.bytes_out + EXCLUDED.bytes_out,
+                    errors = public_api_usage_hourly.errors + EXCLUDED.errors,
+                    rate_limited = public_api_usage_hourly.rate_limited + EXCLUDED.rate_limited,
+                    cache_hits = public_api_usage_hourly.cache_hits + EXCLUDED.cache_hits
+                "#,
+            )
+            .bind(key_id)
+            .bind(hour)
+            .bind(counters.requests)
+            .bind(counters.bytes_out)
+            .bind(counters.errors)
+            .bind(counters.rate_limited)
+            .bind(counters.cache_hits)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to write hourly API usage", e))?;
+        }
+
+        for ((key_id, day, endpoint), (requests, bytes_out)) in &buffer.endpoints {
+            sqlx::query(
+                r#"
+                INSERT INTO public_api_endpoint_usage_daily
+                    (key_id, day, endpoint, requests, bytes_out)
+                VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT (key_id, day, endpoint) DO UPDATE SET
+                    requests = public_api_endpoint_usage_daily.requests + EXCLUDED.requests,
+                    bytes_out = public_api_endpoint_usage_daily.bytes_out + EXCLUDED.bytes_out
+                "#,
+            )
+            .bind(key_id)
+            .bind(day)
+            .bind(endpoint)
+            .bind(requests)
+            .bind(bytes_out)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to write endpoint API usage", e))?;
+        }
+
+        for (key_id, at) in &buffer.last_used {
+            sqlx::query(
+                r#"
+                UPDATE public_api_keys
+                SET last_used_at = GREATEST(COALESCE(last_used_at, $2), $2)
+                WHERE id = $1
+                "#,
+            )
+            .bind(key_id)
+            .bind(at)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to update key last use", e))?;
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| Error::database_with_source("Failed to commit usage flush", e))
+    }
+
+    /// Flush buffered usage every `every`
+    pub fn spawn_usage_flush(self: &Arc<Self>, every: Duration) -> JoinHandle<()> {
+        let service = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(every);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = service.flush_usage().await {
+                    tracing::warn!("Failed to flush public API usage: {}", e);
+                }
+            }
+        })
+    }
+
+    /// Usage over the last `days` days, for every key or just one
+    pub async fn usage_report(
+        &self,
+        days: u32,
+        key_id: Option<Uuid>,
+    ) -> Result<PublicApiUsageReport> {
+        let days = days.clamp(1, MAX_REPORT_DAYS);
+        let since = Utc::now().date_naive() - TimeDelta::days(days as i64 - 1);
+        let since_hour = since.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+
+        let keys: Vec<KeyUsage> = sqlx::query_as(
+            r#"
+            SELECT k.id AS key_id, k.name, k.key_prefix, k.revoked_at IS NOT NULL AS revoked,
+                   k.last_used_at,
+                   COALESCE(SUM(u.requests), 0)::BIGINT AS requests,
+                   COALESCE(SUM(u.bytes_out), 0)::BIGINT AS bytes_out,
+                   COALESCE(SUM(u.errors), 0)::BIGINT AS errors,
+                   COALESCE(SUM(u.rate_limited), 0)::BIGINT AS rate_limited,
+                   COALESCE(SUM(u.cache_hits), 0)::BIGINT AS cache_hits
+            FROM public_api_keys k
+            LEFT JOIN public_api_usage_hourly u ON u.key_id = k.id AND u.hour >= $1
+            WHERE $2::uuid IS NULL OR k.id = $2
+            GROUP BY k.id
+            ORDER BY requests DESC, k.created_at DESC
+            "#,
+        )
+        .bind(since_hour)
+        .bind(key_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load API key usage", e))?;
+
+        let daily: Vec<DailyUsage> = sqlx::query_as(
+            r#"
+            SELECT (hour AT TIME ZONE 'UTC')::date AS day,
+                   SUM(requests)::BIGINT AS requests,
+                   SUM(bytes_out)::BIGINT AS bytes_out,
+                   SUM(errors)::BIGINT AS errors,
+                   SUM(rate_limited)::BIGINT AS rate_limited,
+                   SUM(cache_hits)::BIGINT AS cache_hits
+            FROM public_api_usage_hourly
+            WHERE hour >= $1 AND ($2::uuid IS NULL OR key_id = $2)
+            GROUP BY day
+            ORDER BY day
+            "#,
+        )
+        .bind(since_hour)
+        .bind(key_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load daily API usage", e))?;
+
+        let top_endpoints: Vec<EndpointUsage> = sqlx::query_as(
+            r#"
+            SELECT endpoint, SUM(requests)::BIGINT AS requests, SUM(bytes_out)::BIGINT AS bytes_out
+            FROM public_api_endpoint_usage_daily
+            WHERE day >= $1 AND ($2::uuid IS NULL OR key_id = $2)
+            GROUP BY endpoint
+            ORDER BY requests DESC, endpoint
+            LIMIT 10
+            "#,
+        )
+        .bind(since)
+        .bind(key_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load endpoint API usage", e))?;
+
+        let mut totals = UsageCounters::default();
+        for day in &daily {
+            totals.add(&day.usage);
+        }
+
+        Ok(PublicApiUsageReport {
+            since,
+            totals,
+            keys,
+            daily,
+            top_endpoints,
+        })
+    }
+}
+
+/// Cache key of a public API response: the path and query with the key
+/// itself removed, since responses are shared between keys
+pub fn response_cache_key(path: &str, query: Option<&str>) -> String {
+    let mut pairs: Vec<&str> = query
+        .unwrap_or_default()
+        .split('&')
+        .filter(|pair| !pair.is_empty() && !pair.starts_with("api_key="))
+        .collect();
+    pairs.sort_unstable();
+    format!("public_api_response:{}?{}", path, pairs.join("&"))
+}
+
+/// API key passed in the query string
+pub fn query_api_key(query: Option<&str>) -> Option<&str> {
+    query?
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("api_key="))
+        .filter(|key| !key.is_empty())
+}
+
+/// `scheme://host[:port]` with an http(s) scheme and nothing after it
+fn is_origin(origin: &str) -> bool {
+    let Some(host) = origin
+        .strip_prefix("https://")
+        .or_else(|| origin.strip_prefix("http://"))
+    else {
+        return false;
+    };
+    !host.is_empty()
+        && host
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | ':' | '[' | ']'))
+}
+
+fn generate_secret() -> String {
+    let mut bytes = [0u8; KEY_SECRET_BYTES];
+    OsRng.fill_bytes(&mut bytes);
+    format!(
+        "{}{}",
+        PUBLIC_API_KEY_PREFIX,
+        base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes)
+    )
+}
+
+fn hash_secret(secret: &str) -> String {
+    format!("{:x}", Sha256::digest(secret.as_bytes()))
+}
+
+fn seconds_until_midnight(now: DateTime<Utc>) -> u64 {
+    let tomorrow = now.date_naive() + TimeDelta::days(1);
+    let midnight = tomorrow.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+    (midnight - now).num_seconds().max(1) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_key_input_normalization() {
+        let input = PublicApiKeyInput {
+            name: "  Mobile app ".to_string(),
+            allowed_origins: vec![
+                "https://Example.com/".to_string(),
+                "https://example.com".to_string(),
+            ],
+            ..Default::default()
+        }
+        .normalize()
+        .unwrap();
+        assert_eq!(input.name, "Mobile app");
+        assert_eq!(input.requests_per_minute, Some(DEFAULT_REQUESTS_PER_MINUTE));
+        assert_eq!(input.allowed_origins, vec!["https://example.com"]);
+
+        let invalid = |input: PublicApiKeyInput| input.normalize().is_err();
+        assert!(invalid(PublicApiKeyInput::default()));
+        assert!(invalid(PublicApiKeyInput {
+            name: "x".to_string(),
+            requests_per_minute: Some(MAX_REQUESTS_PER_MINUTE + 1),
+            ..Default::default()
+        }));
+        assert!(invalid(PublicApiKeyInput {
+            name: "x".to_string(),
+            daily_quota: Some(0),
+            ..Default::default()
+        }));
+        assert!(invalid(PublicApiKeyInput {
+            name: "x".to_string(),
+            allowed_origins: vec!["https://example.com/path".to_string()],
+            ..Default::default()
+        }));
+    }
+
+    #[test]
+    fn test_secrets() {
+        let secret = generate_secret();
+        assert!(secret.starts_with(PUBLIC_API_KEY_PREFIX));
+        assert_eq!(secret.len(), PUBLIC_API_KEY_PREFIX.len() + 40);
+        assert_ne!(secret, generate_secret());
+        assert_eq!(hash_secret(&secret).len(), 64);
+    }
+
+    #[test]
+    fn test_response_cache_key_ignores_api_key_and_order() {
+        let a = response_cache_key("/api/public/v1/posts", Some("page=2&api_key=rpk_a&tag=x"));
+        let b = response_cache_key("/api/public/v1/posts", Some("tag=x&page=2&api_key=rpk_b"));
+        assert_eq!(a, b);
+        assert_ne!(
+            a,
+            response_cache_key("/api/public/v1/posts", Some("page=3"))
+        );
+
+        assert_eq!(query_api_key(Some("page=1&api_key=rpk_x")), Some("rpk_x"));
+        assert_eq!(query_api_key(Some("api_key=")), None);
+        assert_eq!(query_api_key(None), None);
+    }
+
+    #[test]
+    fn test_usage_buffer_rolls_up_by_hour_and_endpoint() {
+        let key = Uuid::new_v4();
+        let at = Utc.with_ymd_and_hms(2026, 3, 1, 10, 42, 7).unwrap();
+        let mut buffer = UsageBuffer::default();
+        let served = RequestOutcome::Served {
+            status: 200,
+            cache_hit: true,
+        };
+        buffer.record(key, "/posts", served, 100, at);
+        buffer.record(
+            key,
+            "/posts/:slug",
+            RequestOutcome::Served {
+                status: 404,
+                cache_hit: false,
+            },
+            20,
+            at,
+        );
+        buffer.record(key, "/posts", RequestOutcome::RateLimited, 0, at);
+
+        let hour = Utc.with_ymd_and_hms(2026, 3, 1, 10, 0, 0).unwrap();
+        assert_eq!(
+            buffer.hourly[&(key, hour)],
+            UsageCounters {
+                requests: 3,
+                bytes_out: 120,
+                errors: 1,
+                rate_limited: 1,
+                cache_hits: 1,
+            }
+        );
+        assert_eq!(
+            buffer.endpoints[&(key, at.date_naive(), "/posts".to_string())],
+            (2, 100)
+        );
+
+        let mut retry = UsageBuffer::default();
+        retry.record(key, "/posts", served, 5, at);
+        retry.merge(buffer);
+        assert_eq!(retry.hourly[&(key, hour)].requests, 4);
+        assert_eq!(retry.last_used[&key], at);
+    }
+
+    #[test]
+    fn test_origin_restrictions() {
+        let now = Utc::now();
+        let mut key = PublicApiKey {
+            id: Uuid::nil(),
+            name: "Site".to_string(),
+            key_prefix: "rpk_abcdefgh".to_string(),
+            owner_id: None,
+            requests_per_minute: 60,
+            daily_quota: None,
+            allowed_origins: Json(Vec::new()),
+            last_used_at: None,
+            revoked_at: None,
+            created_at: now,
+            updated_at: now,
+        };
+        assert!(key.allows_origin(Some("https://anywhere.test")));
+
+        key.allowed_origins = Json(vec!["https://example.com".to_string()]);
+        assert!(key.allows_origin(None));
+        assert!(key.allows_origin(Some("https://EXAMPLE.com")));
+        assert!(!key.allows_origin(Some("https://evil.test")));
+
+        let late = Utc.with_ymd_and_hms(2026, 3, 1, 23, 59, 30).unwrap();
+        assert_eq!(seconds_until_midnight(late), 30);
+    }
+}
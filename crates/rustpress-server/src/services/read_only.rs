@@ -0,0 +1,344 @@
+//! Read-Only Mode
+//!
+//! Maintenance mode in which an instance keeps serving reads but answers
+//! writes with `503 Service Unavailable` and a `Retry-After` header, for
+//! use while the primary database fails over or a long migration runs.
+//! Background jobs are paused too, except the cache queue, so warmed pages
+//! stay fresh while nothing else writes.
+//!
+//! GraphQL requests are POSTs, so `/graphql` is let through unless the
+//! operation it runs is a mutation.
+//!
+//! The switch is stored in settings so every instance follows it, and
+//! instances pick up a change through the settings notifications. Each
+//! instance also keeps it in memory, so it still works when the database
+//! does not: a toggle made while the database is down applies to the
+//! instance that received it. Set `RUSTPRESS_READ_ONLY=1` to start an
+//! instance in it; toggle it at runtime through
+//! `/api/v1/maintenance/read-only` or `rustpress server read-only`.
+
+use async_graphql::parser::types::OperationType;
+use axum::http::Method;
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use rustpress_core::error::Result;
+use rustpress_jobs::PauseSwitch;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::sync::atomic::{AtomicBool, Ordering};
+use uuid::Uuid;
+
+use super::json_setting::JsonSetting;
+
+/// Settings key holding the shared read-only switch
+pub const READ_ONLY_SETTINGS_KEY: &str = "read_only_mode";
+
+/// Stored read-only switch
+const READ_ONLY_SETTING: JsonSetting<ReadOnlyStatus> =
+    JsonSetting::new(READ_ONLY_SETTINGS_KEY, "maintenance", "read-only mode");
+
+/// Environment variable that starts the instance in read-only mode
+pub const READ_ONLY_ENV: &str = "RUSTPRESS_READ_ONLY";
+
+/// Endpoint toggling the mode
+pub const READ_ONLY_PATH: &str = "/api/v1/maintenance/read-only";
+
+/// GraphQL endpoint, open in read-only mode for queries
+pub const GRAPHQL_PATH: &str = "/api/v1/graphql";
+
+/// Queue for cache refresh jobs, which keep running in read-only mode
+pub const CACHE_QUEUE: &str = "cache";
+
+/// Default `Retry-After` for refused writes
+pub const DEFAULT_RETRY_AFTER_SECS: u64 = 120;
+
+/// Writes that stay open so administrators can sign in and leave the mode
+const EXEMPT_PATHS: [&str; 5] = [
+    READ_ONLY_PATH,
+    "/api/v1/auth/login",
+    "/api/v1/auth/refresh",
+    "/api/v1/auth/device/code",
+    "/api/v1/auth/device/token",
+];
+
+/// Current read-only state of the instance
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadOnlyStatus {
+    pub enabled: bool,
+    /// Shown to clients whose writes are refused
+    pub reason: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub retry_after_secs: u64,
+    /// `None` when enabled from the environment
+    pub enabled_by: Option<Uuid>,
+    /// Whether background jobs other than cache refreshes are held back
+    pub jobs_paused: bool,
+}
+
+/// Requested change of the mode
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReadOnlyUpdate {
+    pub enabled: bool,
+    #[serde(default)]
+    pub reason: Option<String>,
+    #[serde(default)]
+    pub retry_after_secs: Option<u64>,
+}
+
+impl Default for ReadOnlyStatus {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            reason: None,
+            since: None,
+            retry_after_secs: DEFAULT_RETRY_AFTER_SECS,
+            enabled_by: None,
+            jobs_paused: false,
+        }
+    }
+}
+
+/// Read-only switch shared by every instance through settings
+pub struct ReadOnlyService {
+    pool: PgPool,
+    enabled: AtomicBool,
+    /// Started with `RUSTPRESS_READ_ONLY`; stays on until turned off here
+    forced: AtomicBool,
+    status: RwLock<ReadOnlyStatus>,
+    jobs: PauseSwitch,
+}
+
+impl ReadOnlyService {
+    /// Create the switch; `RUSTPRESS_READ_ONLY` turns it on from the start
+    pub fn new(pool: PgPool, jobs: PauseSwitch) -> Self {
+        let service = Self {
+            pool,
+            enabled: AtomicBool::new(false),
+            forced: AtomicBool::new(false),
+            status: RwLock::new(ReadOnlyStatus::default()),
+            jobs,
+        };
+
+        let from_env = std::env::var(READ_ONLY_ENV)
+            .map(|v| matches!(v.trim(), "1" | "true" | "yes" | "on"))
+            .unwrap_or(false);
+        if from_env {
+            service.forced.store(true, Ordering::SeqCst);
+            service.apply(
+                ReadOnlyUpdate {
+                    enabled: true,
+                    reason: None,
+                    retry_after_secs: None,
+                },
+                None,
+            );
+        }
+
+        service
+    }
+
+    /// Switch that pauses the job worker along with the mode
+    pub fn job_pause(&self) -> PauseSwitch {
+        self.jobs.clone()
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::SeqCst)
+    }
+
+    pub fn status(&self) -> ReadOnlyStatus {
+        self.status.read().clone()
+    }
+
+    /// Enter or leave read-only mode on every instance
+    ///
+    /// The change applies here first, so it takes effect even when it
+    /// cannot be stored; other instances then keep their current mode.
+    pub async fn set(&self, update: ReadOnlyUpdate, user_id: Option<Uuid>) -> ReadOnlyStatus {
+        if !update.enabled {
+            self.forced.store(false, Ordering::SeqCst);
+        }
+        let status = self.apply(update, user_id);
+        if let Err(e) = READ_ONLY_SETTING.save(&self.pool, &status).await {
+            tracing::warn!(
+                "Failed to share read-only mode, only this instance changed: {}",
+                e
+            );
+        }
+        status
+    }
+
+    /// Follow the switch stored in settings
+    pub async fn load(&self) -> Result<()> {
+        let Some(stored) = READ_ONLY_SETTING.load(&self.pool).await? else {
+            return Ok(());
+        };
+        // Nothing to leave, or the environment keeps this instance in it
+        if !stored.enabled && (!self.is_enabled() || self.forced.load(Ordering::SeqCst)) {
+            return Ok(());
+        }
+        self.apply(
+            ReadOnlyUpdate {
+                enabled: stored.enabled,
+                reason: stored.reason,
+                retry_after_secs: Some(stored.retry_after_secs),
+            },
+            stored.enabled_by,
+        );
+        Ok(())
+    }
+
+    /// Change the mode of this instance only
+    fn apply(&self, update: ReadOnlyUpdate, user_id: Option<Uuid>) -> ReadOnlyStatus {
+        let mut status = self.status.write();
+        if update.enabled {
+            if !status.enabled {
+                status.since = Some(Utc::now());
+            }
+            status.reason = update
+                .reason
+                .map(|reason| reason.trim().to_string())
+                .filter(|reason| !reason.is_empty());
+            status.retry_after_secs = update
+                .retry_after_secs
+                .unwrap_or(DEFAULT_RETRY_AFTER_SECS)
+                .max(1);
+            status.enabled_by = user_id;
+            self.jobs.pause();
+            tracing::warn!(reason = ?status.reason, "Read-only mode enabled");
+        } else {
+            status.reason = None;
+            status.since = None;
+            status.enabled_by = None;
+            status.retry_after_secs = DEFAULT_RETRY_AFTER_SECS;
+            self.jobs.resume();
+            if status.enabled {
+                tracing::info!("Read-only mode disabled");
+            }
+        }
+        status.enabled = update.enabled;
+        status.jobs_paused = self.jobs.is_paused();
+        self.enabled.store(update.enabled, Ordering::SeqCst);
+
+        status.clone()
+    }
+
+    /// Whether a request may proceed in read-only mode
+    pub fn allows(method: &Method, path: &str) -> bool {
+        matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+            || EXEMPT_PATHS.contains(&path.trim_end_matches('/'))
+    }
+
+    /// Whether a POST to the GraphQL endpoint is a path [`Self::allows`]
+    /// cannot decide without reading the body
+    pub fn is_graphql(method: &Method, path: &str) -> bool {
+        *method == Method::POST && path.trim_end_matches('/') == GRAPHQL_PATH
+    }
+
+    /// Whether a GraphQL request body only reads: every operation it may
+    /// run, or the one named by `operationName`, is a query. Bodies that
+    /// cannot be parsed are refused; batches must be read-only throughout.
+    pub fn graphql_reads_only(body: &[u8]) -> bool {
+        #[derive(Deserialize)]
+        struct GraphQlRequest {
+            query: String,
+            #[serde(default, rename = "operationName")]
+            operation_name: Option<String>,
+        }
+
+        let requests: Vec<GraphQlRequest> = match serde_json::from_slice(body) {
+            Ok(serde_json::Value::Array(batch)) => batch
+                .into_iter()
+                .filter_map(|request| serde_json::from_value(request).ok())
+                .collect(),
+            Ok(request) => serde_json::from_value(request).into_iter().collect(),
+            Err(_) => return false,
+        };
+        !requests.is_empty()
+            && requests.iter().all(|request| {
+                let Ok(document) = async_graphql::parser::parse_query(&request.query) else {
+                    return false;
+                };
+                document
+                    .operations
+                    .iter()
+                    .filter(|(name, _)| match &request.operation_name {
+                        Some(wanted) => name.is_some_and(|name| name.as_str() == wanted),
+                        None => true,
+                    })
+                    .all(|(_, operation)| operation.node.ty != OperationType::Mutation)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allows() {
+        assert!(ReadOnlyService::allows(&Method::GET, "/api/v1/posts"));
+        assert!(ReadOnlyService::allows(&Method::PUT, READ_ONLY_PATH));
+        assert!(ReadOnlyService::allows(
+            &Method::POST,
+            "/api/v1/auth/login/"
+        ));
+        assert!(!ReadOnlyService::allows(&Method::POST, "/api/v1/posts"));
+        assert!(!ReadOnlyService::allows(&Method::DELETE, "/api/v1/media/1"));
+        assert!(ReadOnlyService::is_graphql(&Method::POST, GRAPHQL_PATH));
+        assert!(!ReadOnlyService::is_graphql(&Method::GET, GRAPHQL_PATH));
+    }
+
+    #[test]
+    fn test_graphql_reads_only() {
+        assert!(ReadOnlyService::graphql_reads_only(
+            br#"{"query": "{ posts { id } }"}"#
+        ));
+        assert!(ReadOnlyService::graphql_reads_only(
+            br#"{"query": "query A { posts { id } } mutation B { logout }", "operationName": "A"}"#
+        ));
+        assert!(!ReadOnlyService::graphql_reads_only(
+            br#"{"query": "query A { posts { id } } mutation B { logout }"}"#
+        ));
+        assert!(!ReadOnlyService::graphql_reads_only(
+            br#"[{"query": "{ posts { id } }"}, {"query": "mutation { logout }"}]"#
+        ));
+        assert!(!ReadOnlyService::graphql_reads_only(b"not json"));
+        assert!(!ReadOnlyService::graphql_reads_only(
+            br#"{"query": "{ posts "}"#
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_toggle_pauses_jobs() {
+        let pool = PgPool::connect_lazy("postgres://localhost/rustpress_test").unwrap();
+        let service = ReadOnlyService::new(pool, PauseSwitch::new());
+        let jobs = service.job_pause();
+
+        let status = service.apply(
+            ReadOnlyUpdate {
+                enabled: true,
+                reason: Some(" Database failover ".to_string()),
+                retry_after_secs: Some(30),
+            },
+            None,
+        );
+        assert!(service.is_enabled());
+        assert!(jobs.is_paused());
+        assert_eq!(status.reason.as_deref(), Some("Database failover"));
+        assert_eq!(status.retry_after_secs, 30);
+        assert!(status.since.is_some());
+
+        let status = service.apply(
+            ReadOnlyUpdate {
+                enabled: false,
+                reason: None,
+                retry_after_secs: None,
+            },
+            None,
+        );
+        assert!(!service.is_enabled());
+        assert!(!jobs.is_paused());
+        assert!(status.since.is_none());
+    }
+}
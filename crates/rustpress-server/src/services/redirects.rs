@@ -0,0 +1,759 @@
+//! Redirects
+//!
+//! Rules for paths that moved or no longer exist: WordPress permalinks
+//! after an import, old slugs after a post or page is renamed in the
+//! editor, and rules administrators add. A rule matches one exact path or,
+//! as a regex, the whole path. Exact paths are checked first, then patterns
+//! in position order. A match answers with 301, 302 or 307 and the rule's
+//! target, or with 410 Gone.
+//!
+//! The [`redirects`](crate::middleware::redirects) middleware checks GET
+//! and HEAD requests for public paths before they are routed. It matches
+//! against an in-memory copy of the enabled rules, reloaded every
+//! [`RULES_CACHE_TTL`] and whenever a rule changes on this node.
+
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use regex::{Regex, RegexBuilder};
+use rustpress_core::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// Status codes a rule can answer with
+pub const REDIRECT_STATUSES: [u16; 4] = [301, 302, 307, 410];
+
+/// Origin of redirects created when a post or page slug changes
+pub const SLUG_CHANGE_ORIGIN: &str = "slug-change";
+
+/// Origin of redirects created through the API
+pub const MANUAL_ORIGIN: &str = "manual";
+
+/// How long the in-memory rules are used before being reloaded
+pub const RULES_CACHE_TTL: Duration = Duration::from_secs(30);
+
+/// Longest source path or pattern kept; longer paths can't be redirected
+const MAX_SOURCE_LEN: usize = 768;
+
+/// Longest target
+const MAX_TARGET_LEN: usize = 2048;
+
+/// Compiled size limit of a pattern, keeping matching cheap
+const MAX_PATTERN_SIZE: usize = 1 << 18;
+
+const REDIRECT_COLUMNS: &str = "id, source_path, match_type, target, status_code, position, \
+     enabled, origin, import_id, hits, last_hit_at, created_at, updated_at";
+
+/// How a rule's source is matched
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchType {
+    /// One path, compared after normalization
+    #[default]
+    Exact,
+    /// A regex matched against the whole normalized path
+    Regex,
+}
+
+impl MatchType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Exact => "exact",
+            Self::Regex => "regex",
+        }
+    }
+}
+
+/// A stored redirect
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct Redirect {
+    pub id: Uuid,
+    /// Normalized path, or pattern for regex rules
+    pub source_path: String,
+    pub match_type: String,
+    /// Site path or absolute URL; empty for 410 Gone
+    pub target: String,
+    pub status_code: i16,
+    /// Order patterns are tried in, lowest first
+    pub position: i32,
+    pub enabled: bool,
+    /// What created the redirect, e.g. `wordpress-import` or `slug-change`
+    pub origin: String,
+    pub import_id: Option<Uuid>,
+    pub hits: i64,
+    pub last_hit_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A redirect to save
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NewRedirect {
+    pub source_path: String,
+    pub target: String,
+}
+
+/// A rule as submitted
+#[derive(Debug, Clone, Deserialize)]
+pub struct RedirectInput {
+    pub source_path: String,
+    #[serde(default)]
+    pub match_type: MatchType,
+    /// Ignored for 410 Gone
+    #[serde(default)]
+    pub target: String,
+    #[serde(default = "default_status")]
+    pub status_code: u16,
+    #[serde(default)]
+    pub position: i32,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_status() -> u16 {
+    301
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl RedirectInput {
+    /// Check the rule, normalizing exact sources and dropping the target
+    /// of 410 rules
+    pub fn normalize(mut self) -> Result<Self> {
+        if !REDIRECT_STATUSES.contains(&self.status_code) {
+            return Err(Error::invalid_input(
+                "status_code",
+                "Must be 301, 302, 307 or 410",
+            ));
+        }
+
+        self.source_path = match self.match_type {
+            MatchType::Exact => normalize_path(&self.source_path).ok_or_else(|| {
+                Error::invalid_input("source_path", "Must be a path other than the site root")
+            })?,
+            MatchType::Regex => {
+                let pattern = self.source_path.trim().to_string();
+                if pattern.is_empty() || pattern.len() > MAX_SOURCE_LEN {
+                    return Err(Error::invalid_input(
+                        "source_path",
+                        format!("Must be 1 to {} characters", MAX_SOURCE_LEN),
+                    ));
+                }
+                compile_pattern(&pattern).map_err(|e| {
+                    Error::invalid_input("source_path", format!("Invalid pattern: {}", e))
+                })?;
+                pattern
+            }
+        };
+
+        self.target = self.target.trim().to_string();
+        if self.status_code == 410 {
+            self.target.clear();
+            return Ok(self);
+        }
+        let site_path = self.target.starts_with('/') && !self.target.starts_with("//");
+        if !site_path && !self.target.starts_with("https://") && !self.target.starts_with("http://")
+        {
+            return Err(Error::invalid_input(
+                "target",
+                "Must be a site path or an http(s) URL",
+            ));
+        }
+        if self.target.len() > MAX_TARGET_LEN {
+            return Err(Error::invalid_input(
+                "target",
+                format!("Must be at most {} characters", MAX_TARGET_LEN),
+            ));
+        }
+        if self.match_type == MatchType::Exact
+            && normalize_path(&self.target).as_deref() == Some(self.source_path.as_str())
+        {
+            return Err(Error::invalid_input(
+                "target",
+                "A redirect can't point at its own source",
+            ));
+        }
+        Ok(self)
+    }
+}
+
+/// Redirect list filters
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RedirectQuery {
+    /// Part of the source or target
+    pub search: Option<String>,
+    pub origin: Option<String>,
+    pub match_type: Option<MatchType>,
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+}
+
+/// What a request path redirects to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ResolvedRedirect {
+    /// The matching rule
+    pub id: Uuid,
+    /// Location, with pattern captures substituted; empty for 410 Gone
+    pub target: String,
+    pub status_code: u16,
+}
+
+impl ResolvedRedirect {
+    pub fn is_gone(&self) -> bool {
+        self.status_code == 410
+    }
+}
+
+/// Normalize a path the way sources are stored: percent-decoded, starting
+/// with `/`, without query string or trailing slash. The site root can't be
+/// redirected.
+pub fn normalize_path(path: &str) -> Option<String> {
+    let path = path.split(['?', '#']).next().unwrap_or_default();
+    let decoded = urlencoding::decode(path)
+        .map(|p| p.into_owned())
+        .unwrap_or_else(|_| path.to_string());
+    let trimmed = decoded.trim().trim_end_matches('/');
+    if trimmed.is_empty() || trimmed.len() > MAX_SOURCE_LEN {
+        return None;
+    }
+    Some(if trimmed.starts_with('/') {
+        trimmed.to_string()
+    } else {
+        format!("/{}", trimmed)
+    })
+}
+
+/// Path of a post, page or event with the given slug
+pub fn content_path(post_type: &str, slug: &str) -> String {
+    match post_type {
+        "page" => format!("/page/{}", slug),
+        "event" => format!("/event/{}", slug),
+        _ => format!("/post/{}", slug),
+    }
+}
+
+/// Percent-encode non-ASCII bytes so a path can be used as a header value
+pub fn encode_location(path: &str) -> String {
+    path.bytes()
+        .map(|b| {
+            if b.is_ascii_graphic() {
+                (b as char).to_string()
+            } else {
+                format!("%{:02X}", b)
+            }
+        })
+        .collect()
+}
+
+/// Compile a pattern anchored to the whole path
+fn compile_pattern(pattern: &str) -> std::result::Result<Regex, regex::Error> {
+    RegexBuilder::new(&format!("^(?:{})$", pattern))
+        .size_limit(MAX_PATTERN_SIZE)
+        .build()
+}
+
+/// Enabled rule as loaded for matching
+#[derive(Debug, Clone, FromRow)]
+struct RuleRow {
+    id: Uuid,
+    source_path: String,
+    match_type: String,
+    target: String,
+    status_code: i16,
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    id: Uuid,
+    target: String,
+    status_code: u16,
+}
+
+impl From<&RuleRow> for Rule {
+    fn from(row: &RuleRow) -> Self {
+        Self {
+            id: row.id,
+            target: row.target.clone(),
+            status_code: row.status_code as u16,
+        }
+    }
+}
+
+/// Enabled rules, ready for matching
+#[derive(Debug, Default)]
+struct RedirectTable {
+    exact: HashMap<String, Rule>,
+    patterns: Vec<(Regex, Rule)>,
+}
+
+impl RedirectTable {
+    /// Rules in the order patterns are tried; patterns that no longer
+    /// compile are skipped
+    fn new(rows: &[RuleRow]) -> Self {
+        let mut table = Self::default();
+        for row in rows {
+            if row.match_type == MatchType::Regex.as_str() {
+                match compile_pattern(&row.source_path) {
+                    Ok(regex) => table.patterns.push((regex, Rule::from(row))),
+                    Err(e) => tracing::warn!(
+                        redirect_id = %row.id,
+                        "Skipping redirect with invalid pattern: {}",
+                        e
+                    ),
+                }
+            } else {
+                table
+                    .exact
+                    .entry(row.source_path.clone())
+                    .or_insert_with(|| Rule::from(row));
+            }
+        }
+        table
+    }
+
+    fn resolve(&self, path: &str) -> Option<ResolvedRedirect> {
+        let path = normalize_path(path)?;
+        if let Some(rule) = self.exact.get(&path) {
+            return Some(ResolvedRedirect {
+                id: rule.id,
+                target: rule.target.clone(),
+                status_code: rule.status_code,
+            });
+        }
+
+        self.patterns.iter().find_map(|(regex, rule)| {
+            let captures = regex.captures(&path)?;
+            let mut target = String::new();
+            captures.expand(&rule.target, &mut target);
+            Some(ResolvedRedirect {
+                id: rule.id,
+                target,
+                status_code: rule.status_code,
+            })
+        })
+    }
+}
+
+fn unique_violation(e: &sqlx::Error) -> bool {
+    matches!(e, sqlx::Error::Database(db) if db.code().as_deref() == Some("23505"))
+}
+
+fn save_error(e: sqlx::Error) -> Error {
+    if unique_violation(&e) {
+        Error::invalid_input("source_path", "A redirect from this source already exists")
+    } else {
+        Error::database_with_source("Failed to save redirect", e)
+    }
+}
+
+/// Stored redirects
+pub struct RedirectService {
+    pool: PgPool,
+    table: Mutex<Option<(Instant, Arc<RedirectTable>)>>,
+}
+
+impl RedirectService {
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            table: Mutex::new(None),
+        }
+    }
+
+    /// Target for a request path, counting the hit
+    pub async fn resolve(&self, path: &str) -> Result<Option<ResolvedRedirect>> {
+        let resolved = self.rules().await?.resolve(path);
+        if let Some(redirect) = &resolved {
+            self.record_hit(redirect.id);
+        }
+        Ok(resolved)
+    }
+
+    /// Target for a request path without counting a hit, for trying rules
+    /// out
+    pub async fn test(&self, path: &str) -> Result<Option<ResolvedRedirect>> {
+        Ok(self.rules().await?.resolve(path))
+    }
+
+    async fn rules(&self) -> Result<Arc<RedirectTable>> {
+        if let Some((loaded_at, table)) = self.table.lock().as_ref() {
+            if loaded_at.elapsed() < RULES_CACHE_TTL {
+                return Ok(table.clone());
+            }
+        }
+
+        let rows: Vec<RuleRow> = sqlx::query_as(
+            "SELECT id, source_path, match_type, target, status_code FROM redirects \
+             WHERE enabled ORDER BY position, created_at",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load redirects", e))?;
+        let table = Arc::new(RedirectTable::new(&rows));
+        *self.table.lock() = Some((Instant::now(), table.clone()));
+        Ok(table)
+    }
+
+    /// Drop the in-memory rules so the next request reloads them
+    fn invalidate(&self) {
+        *self.table.lock() = None;
+    }
+
+    /// Count a hit in the background; requests don't wait for it
+    fn record_hit(&self, id: Uuid) {
+        let pool = self.pool.clone();
+        tokio::spawn(async move {
+            if let Err(e) = sqlx::query(
+                "UPDATE redirects SET hits = hits + 1, last_hit_at = NOW() WHERE id = $1",
+            )
+            .bind(id)
+            .execute(&pool)
+            .await
+            {
+                tracing::warn!(redirect_id = %id, "Failed to count redirect hit: {}", e);
+            }
+        });
+    }
+
+    pub async fn list(&self, query: &RedirectQuery) -> Result<(Vec<Redirect>, u64)> {
+        let page = query.page.unwrap_or(1).max(1);
+        let per_page = query.per_page.unwrap_or(20).clamp(1, 100);
+        let search = query
+            .search
+            .as_deref()
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(|s| format!("%{}%", s.replace('\\', "\\\\").replace(['%', '_'], "\\$0")));
+        let match_type = query.match_type.map(|m| m.as_str());
+
+        const FILTER: &str = "WHERE ($1::text IS NULL OR source_path ILIKE $1 OR target ILIKE $1) \
+             AND ($2::text IS NULL OR origin = $2) AND ($3::text IS NULL OR match_type = $3)";
+
+        let total: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM redirects {}", FILTER))
+            .bind(&search)
+            .bind(&query.origin)
+            .bind(match_type)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to count redirects", e))?;
+
+        let redirects = sqlx::query_as(&format!(
+            "SELECT {} FROM redirects {} ORDER BY match_type, position, source_path \
+             LIMIT $4 OFFSET $5",
+            REDIRECT_COLUMNS, FILTER
+        ))
+        .bind(&search)
+        .bind(&query.origin)
+        .bind(match_type)
+        .bind(per_page as i64)
+        .bind(((page - 1) * per_page) as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to list redirects", e))?;
+
+        Ok((redirects, total as u64))
+    }
+
+    pub async fn get(&self, id: Uuid) -> Result<Redirect> {
+        sqlx::query_as(&format!(
+            "SELECT {} FROM redirects WHERE id = $1",
+            REDIRECT_COLUMNS
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load redirect", e))?
+        .ok_or_else(|| Error::not_found("Redirect", id.to_string()))
+    }
+
+    pub async fn create(&self, input: RedirectInput) -> Result<Redirect> {
+        let input = input.normalize()?;
+        let redirect = sqlx::query_as(&format!(
+            "INSERT INTO redirects (id, source_path, match_type, target, status_code, position, \
+             enabled, origin) VALUES ($1, $2, $3, $4, $5, $6, $7, $8) RETURNING {}",
+            REDIRECT_COLUMNS
+        ))
+        .bind(Uuid::now_v7())
+        .bind(&input.source_path)
+        .bind(input.match_type.as_str())
+        .bind(&input.target)
+        .bind(input.status_code as i16)
+        .bind(input.position)
+        .bind(input.enabled)
+        .bind(MANUAL_ORIGIN)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(save_error)?;
+        self.invalidate();
+        Ok(redirect)
+    }
+
+    pub async fn update(&self, id: Uuid, input: RedirectInput) -> Result<Redirect> {
+        let input = input.normalize()?;
+        let redirect = sqlx::query_as(&format!(
+            "UPDATE redirects SET source_path = $2, match_type = $3, target = $4, \
+             status_code = $5, position = $6, enabled = $7, updated_at = NOW() \
+             WHERE id = $1 RETURNING {}",
+            REDIRECT_COLUMNS
+        ))
+        .bind(id)
+        .bind(&input.source_path)
+        .bind(input.match_type.as_str())
+        .bind(&input.target)
+        .bind(input.status_code as i16)
+        .bind(input.position)
+        .bind(input.enabled)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(save_error)?
+        .ok_or_else(|| Error::not_found("Redirect", id.to_string()))?;
+        self.invalidate();
+        Ok(redirect)
+    }
+
+    pub async fn delete(&self, id: Uuid) -> Result<()> {
+        let deleted = sqlx::query("DELETE FROM redirects WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to delete redirect", e))?;
+        if deleted.rows_affected() == 0 {
+            return Err(Error::not_found("Redirect", id.to_string()));
+        }
+        self.invalidate();
+        Ok(())
+    }
+
+    /// Save permanent redirects, replacing earlier ones with the same
+    /// source. Returns how many were saved.
+    pub async fn save(
+        &self,
+        redirects: &[NewRedirect],
+        origin: &str,
+        import_id: Option<Uuid>,
+    ) -> Result<u64> {
+        let mut saved = 0;
+        for redirect in redirects {
+            let Some(source) = normalize_path(&redirect.source_path) else {
+                continue;
+            };
+            if normalize_path(&redirect.target).as_deref() == Some(source.as_str()) {
+                continue;
+            }
+            saved += sqlx::query(
+                "INSERT INTO redirects (id, source_path, target, origin, import_id) \
+                 VALUES ($1, $2, $3, $4, $5) \
+                 ON CONFLICT (source_path) DO UPDATE SET target = EXCLUDED.target, \
+                 match_type = 'exact', status_code = 301, origin = EXCLUDED.origin, \
+                 import_id = EXCLUDED.import_id, updated_at = NOW()",
+            )
+            .bind(Uuid::now_v7())
+            .bind(&source)
+            .bind(&redirect.target)
+            .bind(origin)
+            .bind(import_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to save redirect", e))?
+            .rows_affected();
+        }
+        self.invalidate();
+        Ok(saved)
+    }
+
+    /// Redirect the old path of renamed content to its new one. Redirects
+    /// pointing at the old path are moved along so they don't chain, and
+    /// one from the new path is dropped since content lives there now.
+    pub async fn record_move(&self, old_path: &str, new_path: &str) -> Result<()> {
+        let (Some(old_path), Some(new_path)) = (normalize_path(old_path), normalize_path(new_path))
+        else {
+            return Ok(());
+        };
+        if old_path == new_path {
+            return Ok(());
+        }
+
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| Error::database_with_source("Failed to start transaction", e))?;
+        sqlx::query("DELETE FROM redirects WHERE source_path = $1 AND match_type = 'exact'")
+            .bind(&new_path)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to save redirect", e))?;
+        sqlx::query(
+            "UPDATE redirects SET target = $2, updated_at = NOW() \
+             WHERE target = $1 AND match_type = 'exact'",
+        )
+        .bind(&old_path)
+        .bind(&new_path)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to save redirect", e))?;
+        sqlx::query(
+            "INSERT INTO redirects (id, source_path, target, origin) VALUES ($1, $2, $3, $4) \
+             ON CONFLICT (source_path) DO UPDATE SET target = EXCLUDED.target, \
+             match_type = 'exact', status_code = 301, enabled = TRUE, \
+             origin = EXCLUDED.origin, updated_at = NOW()",
+        )
+        .bind(Uuid::now_v7())
+        .bind(&old_path)
+        .bind(&new_path)
+        .bind(SLUG_CHANGE_ORIGIN)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to save redirect", e))?;
+        tx.commit()
+            .await
+            .map_err(|e| Error::database_with_source("Failed to save redirect", e))?;
+
+        self.invalidate();
+        Ok(())
+    }
+
+    /// Drop the exact redirect from a path new content now lives at
+    pub async fn release(&self, path: &str) -> Result<()> {
+        let Some(path) = normalize_path(path) else {
+            return Ok(());
+        };
+        let released =
+            sqlx::query("DELETE FROM redirects WHERE source_path = $1 AND match_type = 'exact'")
+                .bind(&path)
+                .execute(&self.pool)
+                .await
+                .map_err(|e| Error::database_with_source("Failed to release redirect", e))?;
+        if released.rows_affected() > 0 {
+            self.invalidate();
+        }
+        Ok(())
+    }
+
+    /// Redirects created by an import
+    pub async fn for_import(&self, import_id: Uuid) -> Result<Vec<Redirect>> {
+        sqlx::query_as(&format!(
+            "SELECT {} FROM redirects WHERE import_id = $1 ORDER BY source_path",
+            REDIRECT_COLUMNS
+        ))
+        .bind(import_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to list redirects", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(source: &str, match_type: MatchType, target: &str, status: i16) -> RuleRow {
+        RuleRow {
+            id: Uuid::new_v4(),
+            source_path: source.to_string(),
+            match_type: match_type.as_str().to_string(),
+            target: target.to_string(),
+            status_code: status,
+        }
+    }
+
+    #[test]
+    fn test_normalize_path() {
+        assert_eq!(
+            normalize_path("/2024/01/15/hello-world/").as_deref(),
+            Some("/2024/01/15/hello-world")
+        );
+        assert_eq!(
+            normalize_path("caf%C3%A9/?utm=x#top").as_deref(),
+            Some("/café")
+        );
+        assert_eq!(normalize_path("/"), None);
+        assert_eq!(normalize_path("?p=12"), None);
+        assert_eq!(normalize_path(&format!("/{}", "a".repeat(800))), None);
+    }
+
+    #[test]
+    fn test_rule_matching() {
+        let table = RedirectTable::new(&[
+            row(
+                r"/blog/(\d{4})/(.+)",
+                MatchType::Regex,
+                "/post/$2?year=$1",
+                301,
+            ),
+            row("/blog/.*", MatchType::Regex, "/blog", 302),
+            row(
+                "/blog/2020/launch",
+                MatchType::Exact,
+                "/post/launch-day",
+                307,
+            ),
+            row("/old-page", MatchType::Exact, "", 410),
+            row("(unclosed", MatchType::Regex, "/never", 301),
+        ]);
+
+        // Exact paths win over earlier patterns
+        let exact = table.resolve("/blog/2020/launch/").unwrap();
+        assert_eq!(exact.target, "/post/launch-day");
+        assert_eq!(exact.status_code, 307);
+
+        let pattern = table.resolve("/blog/2021/hello?ref=x").unwrap();
+        assert_eq!(pattern.target, "/post/hello?year=2021");
+        assert_eq!(pattern.status_code, 301);
+
+        // Patterns are tried in order and match the whole path
+        assert_eq!(table.resolve("/blog/archive").unwrap().target, "/blog");
+        assert!(table.resolve("/en/blog/archive").is_none());
+
+        assert!(table.resolve("/old-page").unwrap().is_gone());
+        assert!(table.resolve("/").is_none());
+    }
+
+    #[test]
+    fn test_input_validation() {
+        let input = |source: &str, match_type, target: &str, status_code| RedirectInput {
+            source_path: source.to_string(),
+            match_type,
+            target: target.to_string(),
+            status_code,
+            position: 0,
+            enabled: true,
+        };
+
+        let exact = input("old/", MatchType::Exact, " /new ", 301)
+            .normalize()
+            .unwrap();
+        assert_eq!(exact.source_path, "/old");
+        assert_eq!(exact.target, "/new");
+
+        let gone = input("/gone", MatchType::Exact, "/ignored", 410)
+            .normalize()
+            .unwrap();
+        assert_eq!(gone.target, "");
+
+        assert!(input("/a", MatchType::Exact, "/b", 308)
+            .normalize()
+            .is_err());
+        assert!(input("/", MatchType::Exact, "/b", 301).normalize().is_err());
+        assert!(input("/a/", MatchType::Exact, "/a", 301)
+            .normalize()
+            .is_err());
+        assert!(input("/a", MatchType::Exact, "//evil.example", 301)
+            .normalize()
+            .is_err());
+        assert!(input("/a", MatchType::Exact, "javascript:x", 301)
+            .normalize()
+            .is_err());
+        assert!(input("(", MatchType::Regex, "/b", 301).normalize().is_err());
+        assert!(
+            input("/a/(.*)", MatchType::Regex, "https://example.com/$1", 302)
+                .normalize()
+                .is_ok()
+        );
+    }
+}
@@ -0,0 +1,114 @@
+//! Multi-region hreflang output.
+//!
+//! Loads the [`RegionMapping`] from settings and turns it into hreflang
+//! alternate links for rendered posts and pages. Every variant is assumed
+//! to serve the same path unless the post overrides it with the
+//! `_hreflang_alternates` meta field (variant ID → path, `null` to skip).
+
+use rustpress_content::regions::{HreflangIssue, RegionMapping};
+use rustpress_core::error::{Error, Result};
+use serde_json::Value;
+use sqlx::PgPool;
+use std::collections::HashMap;
+
+use super::json_setting::JsonSetting;
+
+/// Settings key holding the region mapping
+pub const REGION_MAPPING_SETTINGS_KEY: &str = "region_mapping";
+
+/// Post meta key overriding the path of a post on each variant
+pub const HREFLANG_ALTERNATES_META_KEY: &str = "_hreflang_alternates";
+
+/// Stored region mapping
+const REGION_MAPPING_SETTING: JsonSetting<RegionMapping> =
+    JsonSetting::new(REGION_MAPPING_SETTINGS_KEY, "seo", "region mapping");
+
+/// Load the region mapping from settings; empty when not configured
+pub async fn load_region_mapping(pool: &PgPool) -> Result<RegionMapping> {
+    REGION_MAPPING_SETTING.load_or_default(pool).await
+}
+
+/// Validate and persist the region mapping
+pub async fn save_region_mapping(pool: &PgPool, mapping: &RegionMapping) -> Result<()> {
+    if let Some(issue) = mapping.validate().into_iter().next() {
+        return Err(mapping_error(&issue));
+    }
+    REGION_MAPPING_SETTING.save(pool, mapping).await
+}
+
+fn mapping_error(issue: &HreflangIssue) -> Error {
+    Error::invalid_input("variants", format!("{}: {}", issue.subject, issue.detail))
+}
+
+/// Path of a page on each variant
+pub fn alternates_for(
+    mapping: &RegionMapping,
+    path: &str,
+    meta: Option<&HashMap<String, Value>>,
+) -> HashMap<String, String> {
+    let overrides = meta
+        .and_then(|meta| meta.get(HREFLANG_ALTERNATES_META_KEY))
+        .and_then(Value::as_object);
+
+    mapping
+        .variants
+        .keys()
+        .filter_map(|id| match overrides.and_then(|o| o.get(id)) {
+            Some(Value::String(path)) => Some((id.clone(), path.clone())),
+            Some(_) => None,
+            None => Some((id.clone(), path.to_string())),
+        })
+        .collect()
+}
+
+/// Insert head markup (hreflang links, meta tags, JSON-LD) before `</head>`
+pub fn inject_head_tags(html: &str, tags: &str) -> String {
+    if tags.is_empty() {
+        return html.to_string();
+    }
+    match html.to_ascii_lowercase().find("</head>") {
+        Some(index) => format!("{}{}\n{}", &html[..index], tags, &html[index..]),
+        None => format!("{}\n{}", tags, html),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustpress_content::regions::RegionVariant;
+    use serde_json::json;
+
+    fn mapping() -> RegionMapping {
+        RegionMapping::new()
+            .add_variant(RegionVariant::new("us", "en", "https://example.com").for_country("US"))
+            .add_variant(RegionVariant::new("fr", "fr", "https://example.fr"))
+            .add_variant(RegionVariant::new("de", "de", "https://example.de"))
+            .with_x_default("us")
+    }
+
+    #[test]
+    fn test_alternates_with_overrides() {
+        let meta = HashMap::from([(
+            HREFLANG_ALTERNATES_META_KEY.to_string(),
+            json!({"fr": "/post/bonjour", "de": null}),
+        )]);
+        let alternates = alternates_for(&mapping(), "/post/hello", Some(&meta));
+
+        assert_eq!(alternates.len(), 2);
+        assert_eq!(alternates["us"], "/post/hello");
+        assert_eq!(alternates["fr"], "/post/bonjour");
+    }
+
+    #[test]
+    fn test_inject_hreflang() {
+        let mapping = mapping();
+        let tags = mapping.render_hreflang_tags(&alternates_for(&mapping, "/post/a", None));
+        let html = inject_head_tags("<html><head><title>A</title></head></html>", &tags);
+
+        assert!(html.contains(
+            r#"<link rel="alternate" hreflang="x-default" href="https://example.com/post/a">"#
+        ));
+        assert!(html.find("hreflang=\"fr\"").unwrap() < html.find("</head>").unwrap());
+        assert_eq!(inject_head_tags("<p>x</p>", ""), "<p>x</p>");
+    }
+}
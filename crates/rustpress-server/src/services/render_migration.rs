@@ -0,0 +1,427 @@
+//! Render Pipeline Migration Assist
+//!
+//! Helps large sites move from classic (Tera) templates to block (FSE)
+//! templates. Traffic is shifted to the block pipeline gradually per post
+//! type, using a stable per-post bucket so a given post is always served by
+//! the same pipeline at a given rollout percentage. A stable sample of
+//! posts can be rendered through both pipelines to compare output size
+//! and render time, with a short line diff logged for review. The rollout
+//! configuration is kept in settings so it survives restarts.
+
+use chrono::{DateTime, Utc};
+use rustpress_core::error::Result;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::sync::RwLock;
+
+use super::json_setting::JsonSetting;
+
+/// Settings key holding the migration rollout configuration
+pub const RENDER_MIGRATION_SETTINGS_KEY: &str = "render_migration";
+
+/// Stored rollout configuration
+const RENDER_MIGRATION_SETTING: JsonSetting<RenderMigrationConfig> = JsonSetting::new(
+    RENDER_MIGRATION_SETTINGS_KEY,
+    "themes",
+    "render migration settings",
+);
+
+/// Maximum number of comparisons kept for inspection
+const MAX_COMPARISONS: usize = 200;
+
+/// Maximum number of diff lines kept per comparison
+const MAX_DIFF_LINES: usize = 20;
+
+/// Rendering pipeline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RenderPipeline {
+    /// Classic template hierarchy rendered by Tera
+    Classic,
+    /// Block (FSE) templates with template parts
+    Block,
+}
+
+/// Migration assist configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RenderMigrationConfig {
+    /// Whether the block pipeline is considered at all
+    #[serde(default)]
+    pub enabled: bool,
+    /// Percentage (0-100) of requests rendered through both pipelines
+    #[serde(default)]
+    pub compare_percent: u8,
+    /// Percentage (0-100) of posts served by the block pipeline, per post type
+    #[serde(default)]
+    pub rollout: HashMap<String, u8>,
+}
+
+impl RenderMigrationConfig {
+    /// Load the configuration from settings, falling back to defaults
+    pub async fn load(pool: &PgPool) -> Result<Self> {
+        RENDER_MIGRATION_SETTING.load_or_default(pool).await
+    }
+
+    /// Persist the configuration to settings
+    pub async fn save(&self, pool: &PgPool) -> Result<()> {
+        RENDER_MIGRATION_SETTING.save(pool, self).await
+    }
+
+    /// Clamp percentages to 0-100
+    fn clamped(mut self) -> Self {
+        self.compare_percent = self.compare_percent.min(100);
+        for percent in self.rollout.values_mut() {
+            *percent = (*percent).min(100);
+        }
+        self
+    }
+}
+
+/// Aggregated render metrics for one pipeline
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PipelineStats {
+    pub renders: u64,
+    pub errors: u64,
+    pub total_bytes: u64,
+    pub total_ms: f64,
+}
+
+impl PipelineStats {
+    pub fn avg_bytes(&self) -> f64 {
+        if self.renders == 0 {
+            0.0
+        } else {
+            self.total_bytes as f64 / self.renders as f64
+        }
+    }
+
+    pub fn avg_ms(&self) -> f64 {
+        if self.renders == 0 {
+            0.0
+        } else {
+            self.total_ms / self.renders as f64
+        }
+    }
+}
+
+/// Result of rendering one post through both pipelines
+#[derive(Debug, Clone, Serialize)]
+pub struct RenderComparison {
+    pub slug: String,
+    pub post_type: String,
+    /// Pipeline whose output was served
+    pub served: RenderPipeline,
+    pub classic_bytes: usize,
+    pub block_bytes: usize,
+    pub classic_ms: f64,
+    pub block_ms: f64,
+    pub identical: bool,
+    /// First differing lines (`-` classic, `+` block)
+    pub diff: Vec<String>,
+    /// Block pipeline error, if it failed
+    pub block_error: Option<String>,
+    pub compared_at: DateTime<Utc>,
+}
+
+/// Snapshot of migration state for the admin API
+#[derive(Debug, Clone, Serialize)]
+pub struct RenderMigrationReport {
+    pub config: RenderMigrationConfig,
+    pub stats: HashMap<RenderPipeline, PipelineStats>,
+    pub comparisons: Vec<RenderComparison>,
+}
+
+/// Tracks rollout, metrics and comparisons for the render pipelines
+#[derive(Default)]
+pub struct RenderMigrationAssist {
+    config: RwLock<RenderMigrationConfig>,
+    /// Mirrors `config.enabled` so renders can skip recording without locking
+    enabled: AtomicBool,
+    stats: RwLock<HashMap<RenderPipeline, PipelineStats>>,
+    comparisons: RwLock<VecDeque<RenderComparison>>,
+}
+
+impl RenderMigrationAssist {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn config(&self) -> RenderMigrationConfig {
+        self.config.read().await.clone()
+    }
+
+    pub async fn set_config(&self, config: RenderMigrationConfig) {
+        let config = config.clamped();
+        self.enabled.store(config.enabled, Ordering::Relaxed);
+        *self.config.write().await = config;
+    }
+
+    /// Whether the migration assist is on and renders should be recorded
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Apply the configuration stored in settings
+    pub async fn load(&self, pool: &PgPool) -> Result<()> {
+        self.set_config(RenderMigrationConfig::load(pool).await?)
+            .await;
+        Ok(())
+    }
+
+    /// Persist and apply a new configuration
+    pub async fn update(
+        &self,
+        pool: &PgPool,
+        config: RenderMigrationConfig,
+    ) -> Result<RenderMigrationConfig> {
+        let config = config.clamped();
+        config.save(pool).await?;
+        self.enabled.store(config.enabled, Ordering::Relaxed);
+        *self.config.write().await = config.clone();
+        Ok(config)
+    }
+
+    /// Set the block rollout percentage for a post type
+    pub async fn set_rollout(&self, post_type: &str, percent: u8) {
+        self.config
+            .write()
+            .await
+            .rollout
+            .insert(post_type.to_string(), percent.min(100));
+    }
+
+    /// Pick the pipeline that serves a post and whether to also compare
+    pub async fn plan(&self, post_type: &str, slug: &str) -> (RenderPipeline, bool) {
+        let config = self.config.read().await;
+        if !config.enabled {
+            return (RenderPipeline::Classic, false);
+        }
+
+        let rollout = config.rollout.get(post_type).copied().unwrap_or(0);
+        let pipeline = if bucket(slug) < rollout {
+            RenderPipeline::Block
+        } else {
+            RenderPipeline::Classic
+        };
+
+        // Sample on the slug too, salted so the compared posts are not
+        // just the ones already rolled out to the block pipeline
+        let compare = bucket(&format!("compare:{}", slug)) < config.compare_percent;
+        (pipeline, compare)
+    }
+
+    /// Record a single render
+    pub async fn record_render(
+        &self,
+        pipeline: RenderPipeline,
+        bytes: usize,
+        elapsed: Duration,
+        ok: bool,
+    ) {
+        if !self.is_enabled() {
+            return;
+        }
+        let mut stats = self.stats.write().await;
+        let entry = stats.entry(pipeline).or_default();
+        if ok {
+            entry.renders += 1;
+            entry.total_bytes += bytes as u64;
+            entry.total_ms += elapsed.as_secs_f64() * 1000.0;
+        } else {
+            entry.errors += 1;
+        }
+    }
+
+    /// Record and log a side-by-side comparison
+    pub async fn record_comparison(&self, comparison: RenderComparison) {
+        if comparison.identical {
+            tracing::debug!(slug = %comparison.slug, "Classic and block output identical");
+        } else {
+            tracing::info!(
+                slug = %comparison.slug,
+                post_type = %comparison.post_type,
+                classic_bytes = comparison.classic_bytes,
+                block_bytes = comparison.block_bytes,
+                classic_ms = comparison.classic_ms,
+                block_ms = comparison.block_ms,
+                block_error = ?comparison.block_error,
+                diff = %comparison.diff.join("\n"),
+                "Render pipeline output differs"
+            );
+        }
+
+        let mut comparisons = self.comparisons.write().await;
+        if comparisons.len() >= MAX_COMPARISONS {
+            comparisons.pop_front();
+        }
+        comparisons.push_back(comparison);
+    }
+
+    /// Snapshot current config, metrics and recent comparisons
+    pub async fn report(&self) -> RenderMigrationReport {
+        RenderMigrationReport {
+            config: self.config().await,
+            stats: self.stats.read().await.clone(),
+            comparisons: self
+                .comparisons
+                .read()
+                .await
+                .iter()
+                .rev()
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+/// Stable 0-99 bucket for a slug (FNV-1a, independent of process hashing)
+fn bucket(slug: &str) -> u8 {
+    let hash = slug.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    (hash % 100) as u8
+}
+
+/// Line-based diff summary of two outputs, limited to the first differences
+pub fn diff_lines(classic: &str, block: &str) -> Vec<String> {
+    let classic: Vec<&str> = classic.lines().map(str::trim).collect();
+    let block: Vec<&str> = block.lines().map(str::trim).collect();
+
+    let mut diff = Vec::new();
+    for i in 0..classic.len().max(block.len()) {
+        let (a, b) = (classic.get(i), block.get(i));
+        if a == b {
+            continue;
+        }
+        if let Some(a) = a {
+            diff.push(format!("-{}: {}", i + 1, a));
+        }
+        if let Some(b) = b {
+            diff.push(format!("+{}: {}", i + 1, b));
+        }
+        if diff.len() >= MAX_DIFF_LINES {
+            diff.truncate(MAX_DIFF_LINES);
+            break;
+        }
+    }
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_rollout_is_stable_and_gradual() {
+        let assist = RenderMigrationAssist::new();
+        assert_eq!(
+            assist.plan("post", "hello-world").await,
+            (RenderPipeline::Classic, false)
+        );
+
+        assist
+            .set_config(RenderMigrationConfig {
+                enabled: true,
+                compare_percent: 0,
+                rollout: HashMap::from([("post".to_string(), 100)]),
+            })
+            .await;
+        assert_eq!(
+            assist.plan("post", "hello-world").await.0,
+            RenderPipeline::Block
+        );
+        assert_eq!(
+            assist.plan("page", "about").await.0,
+            RenderPipeline::Classic
+        );
+
+        assist.set_rollout("post", 30).await;
+        let slugs: Vec<String> = (0..1000).map(|i| format!("post-{}", i)).collect();
+        let mut block = 0;
+        for slug in &slugs {
+            let first = assist.plan("post", slug).await.0;
+            assert_eq!(assist.plan("post", slug).await.0, first);
+            if first == RenderPipeline::Block {
+                block += 1;
+            }
+        }
+        assert!((200..400).contains(&block), "block share was {}", block);
+    }
+
+    #[tokio::test]
+    async fn test_compare_is_sampled_per_slug() {
+        let assist = RenderMigrationAssist::new();
+        assist
+            .set_config(RenderMigrationConfig {
+                enabled: true,
+                compare_percent: 50,
+                rollout: HashMap::new(),
+            })
+            .await;
+
+        let mut compared = 0;
+        for i in 0..1000 {
+            let slug = format!("post-{}", i);
+            let first = assist.plan("post", &slug).await.1;
+            assert_eq!(assist.plan("post", &slug).await.1, first);
+            if first {
+                compared += 1;
+            }
+        }
+        assert!((350..650).contains(&compared), "compared {}", compared);
+    }
+
+    #[tokio::test]
+    async fn test_metrics_and_comparisons() {
+        let assist = RenderMigrationAssist::new();
+        assist
+            .record_render(RenderPipeline::Classic, 1, Duration::ZERO, true)
+            .await;
+        assert!(assist.report().await.stats.is_empty());
+
+        assist
+            .set_config(RenderMigrationConfig {
+                enabled: true,
+                ..Default::default()
+            })
+            .await;
+        assist
+            .record_render(
+                RenderPipeline::Classic,
+                1000,
+                Duration::from_millis(4),
+                true,
+            )
+            .await;
+        assist
+            .record_render(RenderPipeline::Block, 0, Duration::ZERO, false)
+            .await;
+
+        assist
+            .record_comparison(RenderComparison {
+                slug: "hello".into(),
+                post_type: "post".into(),
+                served: RenderPipeline::Classic,
+                classic_bytes: 10,
+                block_bytes: 12,
+                classic_ms: 1.0,
+                block_ms: 2.0,
+                identical: false,
+                diff: diff_lines("<p>a</p>", "<p>b</p>"),
+                block_error: None,
+                compared_at: Utc::now(),
+            })
+            .await;
+
+        let report = assist.report().await;
+        assert_eq!(report.stats[&RenderPipeline::Classic].avg_bytes(), 1000.0);
+        assert_eq!(report.stats[&RenderPipeline::Block].errors, 1);
+        assert_eq!(
+            report.comparisons[0].diff,
+            vec!["-1: <p>a</p>", "+1: <p>b</p>"]
+        );
+    }
+}
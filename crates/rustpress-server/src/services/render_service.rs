@@ -3,8 +3,12 @@
 //! Renders the public-facing website using the active theme's templates.
 //! Handles WordPress-like template hierarchy for different content types.
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
+use rustpress_content::regions::RegionMapping;
+use rustpress_core::config::current_environment;
 use rustpress_core::error::{Error, Result};
+use rustpress_core::tenant::site_filter;
+use rustpress_themes::fse::FseManager;
 use rustpress_themes::templates::{QueryContext, TemplateEngine};
 use serde::{Deserialize, Serialize};
 use sqlx::{FromRow, PgPool};
@@ -15,12 +19,44 @@ use tera::Context;
 use tokio::sync::RwLock;
 use uuid::Uuid;
 
+use super::archives::{ArchiveQuery, DateArchive};
+use super::avatar::{avatar_url_for, DEFAULT_AVATAR_SIZE};
+use super::cache_policy::{CacheOverride, SurrogateKeys};
+use super::compliance::ContentDescriptor;
+use super::content_filters::{inject_shortlink, ContentFilterService};
+use super::events::{event_json_ld, render_ical, CalendarEntry, Event, EventData, EVENT_POST_TYPE};
+use super::feeds::{
+    last_modified, render_feed, render_podcast_rss, FeedChannel, FeedFormat, FeedScope,
+    RenderedFeed,
+};
+use super::menus::MenuService;
+use super::podcast::{Episode, EpisodeData, PodcastConfig, EPISODE_POST_TYPE, PODCAST_FEED_SIZE};
+use super::regions::{alternates_for, inject_head_tags, load_region_mapping};
+use super::render_migration::{
+    diff_lines, RenderComparison, RenderMigrationAssist, RenderPipeline,
+};
+use super::responsive_images::ResponsiveImagesService;
+use super::robots::{inject_robots_meta, RobotsConfig};
+use super::taxonomy::{descendant_ids, Taxonomy, CATEGORY_TAXONOMY, TAG_TAXONOMY};
+use super::user_profile::{inject_json_ld, ProfileService, ProfileView, ProfileViewer};
+use super::web_vitals::WebVitalsService;
+use super::widgets::WidgetService;
 use super::ThemeService;
 
+/// Posts per archive page
+const ARCHIVE_PER_PAGE: i32 = 10;
+
+/// Posts per feed
+const FEED_SIZE: i32 = 20;
+
+/// Days ended events stay in the iCalendar feed
+const ICAL_HISTORY_DAYS: i64 = 365;
+
 /// Database row for posts
 #[derive(Debug, FromRow)]
 struct PostRow {
     id: Uuid,
+    short_id: Option<i64>,
     title: String,
     slug: String,
     content: Option<String>,
@@ -73,45 +109,6 @@ struct MediaRow {
     mime_type: String,
 }
 
-/// Database row for menus
-#[derive(Debug, FromRow)]
-struct MenuRow {
-    id: Uuid,
-    name: String,
-    slug: String,
-}
-
-/// Database row for menu items
-#[derive(Debug, FromRow)]
-struct MenuItemRow {
-    id: Uuid,
-    title: String,
-    url: String,
-    target: Option<String>,
-    css_classes: Option<String>,
-    parent_id: Option<Uuid>,
-    #[allow(dead_code)]
-    position: i32,
-}
-
-/// Database row for widget areas
-#[derive(Debug, FromRow)]
-struct WidgetAreaRow {
-    id: Uuid,
-    slug: String,
-    name: String,
-}
-
-/// Database row for widgets
-#[derive(Debug, FromRow)]
-struct WidgetRow {
-    id: Uuid,
-    widget_type: String,
-    title: Option<String>,
-    content: Option<String>,
-    settings: serde_json::Value,
-}
-
 /// Site metadata for templates
 #[derive(Debug, Clone, Serialize)]
 pub struct SiteInfo {
@@ -128,6 +125,11 @@ pub struct SiteInfo {
 #[derive(Debug, Clone, Serialize)]
 pub struct PostData {
     pub id: String,
+    /// Numeric id used by `/?p=` shortlinks
+    pub short_id: Option<i64>,
+    /// Shortlink URL, when shortlinks are enabled
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shortlink: Option<String>,
     pub title: String,
     pub slug: String,
     pub content: String,
@@ -143,6 +145,12 @@ pub struct PostData {
     pub published_at: Option<DateTime<Utc>>,
     pub comment_count: i32,
     pub meta: HashMap<String, serde_json::Value>,
+    /// Audio enclosure of podcast episodes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub episode: Option<EpisodeData>,
+    /// Schedule and venue of events
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event: Option<EventData>,
 }
 
 /// Author data for templates
@@ -154,6 +162,9 @@ pub struct AuthorData {
     pub bio: Option<String>,
     pub avatar_url: Option<String>,
     pub url: Option<String>,
+    /// Public profile fields and social links (author archives only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<ProfileView>,
 }
 
 /// Media/attachment data
@@ -205,6 +216,7 @@ pub struct WidgetData {
     pub id: String,
     pub widget_type: String,
     pub title: Option<String>,
+    /// Rendered body of the widget
     pub content: String,
     pub settings: serde_json::Value,
 }
@@ -214,7 +226,10 @@ pub struct WidgetData {
 pub struct WidgetAreaData {
     pub slug: String,
     pub name: String,
+    pub description: Option<String>,
     pub widgets: Vec<WidgetData>,
+    /// The widgets in the area's wrapper markup, ready to output
+    pub html: String,
 }
 
 /// Pagination data
@@ -228,6 +243,8 @@ pub struct PaginationData {
     pub has_next: bool,
     pub previous_url: Option<String>,
     pub next_url: Option<String>,
+    /// Canonical URL of the current page
+    pub canonical_url: String,
 }
 
 /// Archive data
@@ -243,6 +260,66 @@ pub struct ArchiveData {
     pub day: Option<i32>,
 }
 
+/// Archive query resolved against the database
+struct ResolvedArchive {
+    archive: ArchiveData,
+    query: QueryContext,
+    terms: Vec<TermData>,
+    /// Post types listed by a term archive
+    post_types: Vec<String>,
+    author: Option<AuthorData>,
+    date: Option<DateArchive>,
+}
+
+impl ResolvedArchive {
+    /// Surrogate keys for an archive listing or feed
+    fn surrogate_keys(&self) -> SurrogateKeys {
+        let keys = SurrogateKeys::new()
+            .post_type("post")
+            .post_type(EPISODE_POST_TYPE);
+        let keys = self
+            .terms
+            .iter()
+            .fold(keys, |keys, term| keys.term(&term.taxonomy, &term.id));
+        match &self.author {
+            Some(author) => keys.user(&author.id),
+            None => keys,
+        }
+    }
+
+    /// Insert the archive-specific template variables
+    fn insert_into(&self, context: &mut Context) {
+        context.insert("archive", &self.archive);
+        if let Some(ref term) = self.archive.term {
+            context.insert("term", term);
+        }
+        context.insert("terms", &self.terms);
+        if let Some(category) = self.terms.iter().find(|t| t.taxonomy == CATEGORY_TAXONOMY) {
+            context.insert("category", category);
+        }
+        if let Some(tag) = self.terms.iter().find(|t| t.taxonomy == TAG_TAXONOMY) {
+            context.insert("tag", tag);
+        }
+        if let Some(ref author) = self.author {
+            context.insert("author", author);
+        }
+        context.insert("is_category", &self.query.is_category);
+        context.insert("is_tag", &self.query.is_tag);
+        context.insert("is_tax", &self.query.is_tax);
+        context.insert("is_author", &self.query.is_author);
+        context.insert("is_date", &self.query.is_date);
+    }
+}
+
+/// Resource name used in not-found errors for a taxonomy
+fn term_label(taxonomy: &str) -> &'static str {
+    match taxonomy {
+        "category" => "Category",
+        "tag" => "Tag",
+        _ => "Term",
+    }
+}
+
 /// Rendered page response
 #[derive(Debug)]
 pub struct RenderedPage {
@@ -250,6 +327,30 @@ pub struct RenderedPage {
     pub status_code: u16,
     pub cache_control: String,
     pub content_type: String,
+    /// Surrogate keys for the content shown on the page
+    pub surrogate_keys: Vec<String>,
+    /// Per-post cache policy override
+    pub cache_override: Option<CacheOverride>,
+    /// Compliance flags of the post shown (`content_flags` meta)
+    pub content_flags: Vec<String>,
+}
+
+impl RenderedPage {
+    /// Attach surrogate keys for edge cache invalidation
+    fn with_keys(mut self, keys: SurrogateKeys) -> Self {
+        self.surrogate_keys = keys.build();
+        self
+    }
+
+    /// Attach the keys and cache override of a single post or page
+    fn for_post(mut self, post: &PostData) -> Self {
+        self.cache_override = CacheOverride::from_meta(&post.meta);
+        self.content_flags = ContentDescriptor::flags_from_meta(&post.meta);
+        if let Some(ref shortlink) = post.shortlink {
+            self.html = inject_shortlink(&self.html, shortlink);
+        }
+        self.with_keys(SurrogateKeys::for_post(post))
+    }
 }
 
 /// Public rendering service
@@ -258,7 +359,17 @@ pub struct RenderService {
     theme_service: Arc<ThemeService>,
     themes_dir: PathBuf,
     template_engines: Arc<RwLock<HashMap<String, Arc<TemplateEngine>>>>,
+    fse_managers: Arc<RwLock<HashMap<String, Arc<FseManager>>>>,
     site_info: Arc<RwLock<SiteInfo>>,
+    migration: Arc<RenderMigrationAssist>,
+    robots: Arc<RwLock<Option<Arc<RobotsConfig>>>>,
+    regions: Arc<RwLock<Option<Arc<RegionMapping>>>>,
+    profiles: Option<Arc<ProfileService>>,
+    content_filters: Option<Arc<ContentFilterService>>,
+    menus: Option<Arc<MenuService>>,
+    widgets: Option<Arc<WidgetService>>,
+    web_vitals: Option<Arc<WebVitalsService>>,
+    responsive_images: Option<Arc<ResponsiveImagesService>>,
 }
 
 impl RenderService {
@@ -269,6 +380,7 @@ impl RenderService {
             theme_service,
             themes_dir,
             template_engines: Arc::new(RwLock::new(HashMap::new())),
+            fse_managers: Arc::new(RwLock::new(HashMap::new())),
             site_info: Arc::new(RwLock::new(SiteInfo {
                 name: "RustPress Site".to_string(),
                 description: "Powered by RustPress".to_string(),
@@ -279,9 +391,188 @@ impl RenderService {
                     .to_string(),
                 author: "RustPress".to_string(),
             })),
+            migration: Arc::new(RenderMigrationAssist::new()),
+            robots: Arc::new(RwLock::new(None)),
+            regions: Arc::new(RwLock::new(None)),
+            profiles: None,
+            content_filters: None,
+            menus: None,
+            widgets: None,
+            web_vitals: None,
+            responsive_images: None,
         }
     }
 
+    /// Show public profile fields on author archives
+    pub fn with_profiles(mut self, profiles: Arc<ProfileService>) -> Self {
+        self.profiles = Some(profiles);
+        self
+    }
+
+    /// Run post and page content through the content filter chain
+    pub fn with_content_filters(mut self, content_filters: Arc<ContentFilterService>) -> Self {
+        self.content_filters = Some(content_filters);
+        self
+    }
+
+    /// Fill the theme's menu locations
+    pub fn with_menus(mut self, menus: Arc<MenuService>) -> Self {
+        self.menus = Some(menus);
+        self
+    }
+
+    /// Fill the theme's widget areas
+    pub fn with_widgets(mut self, widgets: Arc<WidgetService>) -> Self {
+        self.widgets = Some(widgets);
+        self
+    }
+
+    /// Add the Core Web Vitals measuring script to pages while collection is on
+    pub fn with_web_vitals(mut self, web_vitals: Arc<WebVitalsService>) -> Self {
+        self.web_vitals = Some(web_vitals);
+        self
+    }
+
+    /// Size images and set how they and iframes load on rendered pages
+    pub fn with_responsive_images(
+        mut self,
+        responsive_images: Arc<ResponsiveImagesService>,
+    ) -> Self {
+        self.responsive_images = Some(responsive_images);
+        self
+    }
+
+    /// Classic-to-block migration assist (rollout, metrics, comparisons)
+    pub fn migration(&self) -> &Arc<RenderMigrationAssist> {
+        &self.migration
+    }
+
+    /// Public site URL
+    pub async fn site_url(&self) -> String {
+        self.site_info.read().await.url.clone()
+    }
+
+    /// Site name, URL and other metadata
+    pub async fn site_info(&self) -> SiteInfo {
+        self.site_info.read().await.clone()
+    }
+
+    /// Robots configuration, loaded from settings on first use
+    pub async fn robots_config(&self) -> Arc<RobotsConfig> {
+        if let Some(config) = self.robots.read().await.clone() {
+            return config;
+        }
+
+        match RobotsConfig::load(&self.pool).await {
+            Ok(config) => {
+                let config = Arc::new(config);
+                *self.robots.write().await = Some(config.clone());
+                config
+            }
+            Err(e) => {
+                tracing::warn!("Failed to load robots settings, using defaults: {}", e);
+                Arc::new(RobotsConfig::default())
+            }
+        }
+    }
+
+    /// Replace the cached robots configuration after it was saved
+    pub async fn set_robots_config(&self, config: RobotsConfig) {
+        *self.robots.write().await = Some(Arc::new(config));
+    }
+
+    /// Apply the content filter chain and attach the post's shortlink
+    async fn filter_content(&self, mut post: PostData) -> PostData {
+        if let Some(ref filters) = self.content_filters {
+            post.content = filters.apply(std::mem::take(&mut post.content)).await;
+            post.shortlink = filters
+                .shortlink(&self.site_url().await, post.short_id)
+                .await;
+        }
+        post
+    }
+
+    /// Add the robots meta tag required by the environment or post settings
+    async fn apply_robots(
+        &self,
+        html: String,
+        post_meta: Option<&HashMap<String, serde_json::Value>>,
+    ) -> String {
+        let config = self.robots_config().await;
+        match config.meta_directives(&current_environment(), post_meta) {
+            Some(directives) => inject_robots_meta(&html, &directives),
+            None => html,
+        }
+    }
+
+    /// Add the Core Web Vitals measuring script while collection is on
+    async fn apply_web_vitals(&self, html: String) -> String {
+        let Some(web_vitals) = &self.web_vitals else {
+            return html;
+        };
+        match web_vitals.beacon_script().await {
+            Some(script) => inject_head_tags(&html, &script),
+            None => html,
+        }
+    }
+
+    /// Add image dimensions, srcsets and lazy loading
+    async fn apply_responsive_images(&self, html: String) -> String {
+        match &self.responsive_images {
+            Some(responsive_images) => responsive_images.apply(html).await,
+            None => html,
+        }
+    }
+
+    /// Region mapping, loaded from settings on first use
+    pub async fn region_mapping(&self) -> Arc<RegionMapping> {
+        if let Some(mapping) = self.regions.read().await.clone() {
+            return mapping;
+        }
+
+        match load_region_mapping(&self.pool).await {
+            Ok(mapping) => {
+                let mapping = Arc::new(mapping);
+                *self.regions.write().await = Some(mapping.clone());
+                mapping
+            }
+            Err(e) => {
+                tracing::warn!("Failed to load region mapping, skipping hreflang: {}", e);
+                Arc::new(RegionMapping::default())
+            }
+        }
+    }
+
+    /// Replace the cached region mapping after it was saved
+    pub async fn set_region_mapping(&self, mapping: RegionMapping) {
+        *self.regions.write().await = Some(Arc::new(mapping));
+    }
+
+    /// Drop the cached robots configuration; the next page reloads it
+    pub async fn invalidate_robots_config(&self) {
+        *self.robots.write().await = None;
+    }
+
+    /// Drop the cached region mapping; the next page reloads it
+    pub async fn invalidate_region_mapping(&self) {
+        *self.regions.write().await = None;
+    }
+
+    /// Add hreflang and x-default links for the page's regional variants
+    async fn apply_hreflang(
+        &self,
+        mut page: RenderedPage,
+        path: &str,
+        post_meta: &HashMap<String, serde_json::Value>,
+    ) -> RenderedPage {
+        let mapping = self.region_mapping().await;
+        if !mapping.variants.is_empty() {
+            let alternates = alternates_for(&mapping, path, Some(post_meta));
+            page.html = inject_head_tags(&page.html, &mapping.render_hreflang_tags(&alternates));
+        }
+        page
+    }
+
     /// Update site info from settings
     pub async fn update_site_info(&self, info: SiteInfo) {
         *self.site_info.write().await = info;
@@ -305,6 +596,9 @@ impl RenderService {
         engine
             .init()
             .map_err(|e| Error::internal(format!("Failed to initialize templates: {}", e)))?;
+        engine.register_function("avatar_url", super::avatar::tera_avatar_url);
+        engine.register_function("og_image_url", super::og_image::tera_og_image_url);
+        engine.register_function("social_card_url", super::og_image::tera_og_image_url);
 
         let engine = Arc::new(engine);
 
@@ -341,244 +635,30 @@ impl RenderService {
         // Current year for copyright
         context.insert("current_year", &Utc::now().format("%Y").to_string());
 
-        // Menus - would load from database
+        // Menus
         let menus = self.load_menus(theme_id).await.unwrap_or_default();
         context.insert("menus", &menus);
 
-        // Widget areas - would load from database
+        // Widget areas
         let widget_areas = self.load_widget_areas(theme_id).await.unwrap_or_default();
         context.insert("sidebars", &widget_areas);
 
         context
     }
 
-    /// Load menus for theme
+    /// Load menus for theme, as anonymous visitors see them
     async fn load_menus(&self, theme_id: &str) -> Result<HashMap<String, MenuData>> {
-        let mut menus = HashMap::new();
-
-        // Get menu assignments for this theme
-        let assignments = self.theme_service.get_menu_assignments(theme_id).await?;
-
-        // Load menus from database
-        let menu_rows: Vec<MenuRow> =
-            sqlx::query_as("SELECT id, name, slug FROM menus WHERE deleted_at IS NULL")
-                .fetch_all(&self.pool)
-                .await
-                .map_err(|e| Error::database_with_source("Failed to load menus", e))?;
-
-        for menu_row in menu_rows {
-            // Load menu items for this menu
-            let item_rows: Vec<MenuItemRow> = sqlx::query_as(
-                r#"
-                SELECT id, title, url, target, css_classes, parent_id, position
-                FROM menu_items
-                WHERE menu_id = $1
-                ORDER BY position ASC
-                "#,
-            )
-            .bind(menu_row.id)
-            .fetch_all(&self.pool)
-            .await
-            .map_err(|e| Error::database_with_source("Failed to load menu items", e))?;
-
-            // Build hierarchical menu structure
-            let items = self.build_menu_hierarchy(&item_rows);
-
-            // Check if this menu is assigned to a location in the theme
-            let location = assignments
-                .as_array()
-                .and_then(|arr| {
-                    arr.iter()
-                        .find(|a| {
-                            a.get("menu_id")
-                                .and_then(|m| m.as_str())
-                                .map(|mid| mid == menu_row.id.to_string())
-                                .unwrap_or(false)
-                        })
-                        .and_then(|a| a.get("location_slug"))
-                        .and_then(|l| l.as_str())
-                        .map(|s| s.to_string())
-                })
-                .unwrap_or_else(|| menu_row.slug.clone());
-
-            menus.insert(
-                location,
-                MenuData {
-                    name: menu_row.name,
-                    slug: menu_row.slug,
-                    items,
-                },
-            );
+        match self.menus {
+            Some(ref menus) => menus.theme_menus(theme_id, None).await,
+            None => Ok(HashMap::new()),
         }
-
-        // Add empty placeholders for common menu locations if not assigned
-        for loc in ["primary", "footer", "social"] {
-            if !menus.contains_key(loc) {
-                menus.insert(
-                    loc.to_string(),
-                    MenuData {
-                        name: format!("{} Menu", loc),
-                        slug: loc.to_string(),
-                        items: vec![],
-                    },
-                );
-            }
-        }
-
-        Ok(menus)
-    }
-
-    /// Build hierarchical menu structure from flat list
-    fn build_menu_hierarchy(&self, items: &[MenuItemRow]) -> Vec<MenuItemData> {
-        let mut root_items: Vec<MenuItemData> = Vec::new();
-        let mut children_map: HashMap<String, Vec<MenuItemData>> = HashMap::new();
-
-        // First pass: create all items and group by parent
-        for item in items {
-            let menu_item = MenuItemData {
-                id: item.id.to_string(),
-                title: item.title.clone(),
-                url: item.url.clone(),
-                target: item.target.clone(),
-                classes: item
-                    .css_classes
-                    .clone()
-                    .map(|s| s.split_whitespace().map(|s| s.to_string()).collect())
-                    .unwrap_or_else(|| vec!["menu-item".to_string()]),
-                parent_id: item.parent_id.map(|id| id.to_string()),
-                children: vec![],
-            };
-
-            if let Some(parent_id) = &item.parent_id {
-                children_map
-                    .entry(parent_id.to_string())
-                    .or_default()
-                    .push(menu_item);
-            } else {
-                root_items.push(menu_item);
-            }
-        }
-
-        // Second pass: attach children to parents
-        fn attach_children(
-            item: &mut MenuItemData,
-            children_map: &HashMap<String, Vec<MenuItemData>>,
-        ) {
-            if let Some(children) = children_map.get(&item.id) {
-                item.children = children.clone();
-                for child in &mut item.children {
-                    attach_children(child, children_map);
-                }
-            }
-        }
-
-        for item in &mut root_items {
-            attach_children(item, &children_map);
-        }
-
-        root_items
     }
 
-    /// Load widget areas for theme
-    async fn load_widget_areas(&self, _theme_id: &str) -> Result<HashMap<String, WidgetAreaData>> {
-        let mut areas = HashMap::new();
-
-        // Load widget areas from database
-        let area_rows: Vec<WidgetAreaRow> = sqlx::query_as(
-            "SELECT id, slug, name FROM widget_areas WHERE is_active = true ORDER BY position",
-        )
-        .fetch_all(&self.pool)
-        .await
-        .map_err(|e| Error::database_with_source("Failed to load widget areas", e))?;
-
-        for area_row in area_rows {
-            // Load widgets for this area
-            let widget_rows: Vec<WidgetRow> = sqlx::query_as(
-                r#"
-                SELECT id, widget_type, title, content, settings
-                FROM widgets
-                WHERE sidebar_id = $1 AND is_active = true
-                ORDER BY position ASC
-                "#,
-            )
-            .bind(area_row.id)
-            .fetch_all(&self.pool)
-            .await
-            .map_err(|e| Error::database_with_source("Failed to load widgets", e))?;
-
-            let widgets: Vec<WidgetData> = widget_rows
-                .into_iter()
-                .map(|w| {
-                    let content =
-                        self.render_widget_content(&w.widget_type, &w.content, &w.settings);
-                    WidgetData {
-                        id: w.id.to_string(),
-                        widget_type: w.widget_type,
-                        title: w.title,
-                        content,
-                        settings: w.settings,
-                    }
-                })
-                .collect();
-
-            areas.insert(
-                area_row.slug.clone(),
-                WidgetAreaData {
-                    slug: area_row.slug,
-                    name: area_row.name,
-                    widgets,
-                },
-            );
-        }
-
-        // Add default empty areas for common sidebar locations if not present
-        for (slug, name) in [
-            ("sidebar", "Main Sidebar"),
-            ("footer-1", "Footer Column 1"),
-            ("footer-2", "Footer Column 2"),
-            ("footer-3", "Footer Column 3"),
-        ] {
-            if !areas.contains_key(slug) {
-                areas.insert(
-                    slug.to_string(),
-                    WidgetAreaData {
-                        slug: slug.to_string(),
-                        name: name.to_string(),
-                        widgets: vec![],
-                    },
-                );
-            }
-        }
-
-        Ok(areas)
-    }
-
-    /// Render widget content based on widget type
-    fn render_widget_content(
-        &self,
-        widget_type: &str,
-        content: &Option<String>,
-        settings: &serde_json::Value,
-    ) -> String {
-        match widget_type {
-            "search" => r#"<form role="search" method="get" action="/search" class="widget-search">
-                <input type="search" name="q" placeholder="Search..." class="search-input">
-                <button type="submit" class="search-button">Search</button>
-            </form>"#
-                .to_string(),
-            "text" | "html" => content.clone().unwrap_or_default(),
-            "recent_posts" => {
-                // Placeholder - would be populated with actual recent posts
-                "<ul class=\"widget-recent-posts\"><!-- Dynamic content --></ul>".to_string()
-            }
-            "categories" => {
-                // Placeholder - would be populated with actual categories
-                "<ul class=\"widget-categories\"><!-- Dynamic content --></ul>".to_string()
-            }
-            "tag_cloud" => {
-                "<div class=\"widget-tag-cloud\"><!-- Dynamic content --></div>".to_string()
-            }
-            _ => content.clone().unwrap_or_default(),
+    /// Load rendered widget areas for theme
+    async fn load_widget_areas(&self, theme_id: &str) -> Result<HashMap<String, WidgetAreaData>> {
+        match self.widgets {
+            Some(ref widgets) => widgets.theme_areas(theme_id).await,
+            None => Ok(HashMap::new()),
         }
     }
 
@@ -615,39 +695,215 @@ impl RenderService {
             ..Default::default()
         };
 
-        self.render_with_engine(&engine, &query, &context).await
+        let page = self
+            .render_with_engine(&engine, &query, &context, None)
+            .await?;
+        Ok(page.with_keys(
+            SurrogateKeys::new()
+                .post_type("post")
+                .post_type(EPISODE_POST_TYPE),
+        ))
     }
 
-    /// Render a single post
+    /// Render a single post.
+    ///
+    /// When the migration assist is enabled, the post may be served by the
+    /// block pipeline and/or rendered through both pipelines for comparison.
+    /// Block rendering failures always fall back to the classic output.
     pub async fn render_post(
         &self,
         slug: &str,
         preview_token: Option<&str>,
     ) -> Result<RenderedPage> {
         let theme_id = self.get_active_theme_id(preview_token).await?;
-        let engine = self.get_engine(&theme_id).await?;
-
-        let mut context = self.build_base_context(&theme_id).await;
 
         // Load the post
         let post = self
             .load_post_by_slug(slug)
             .await?
             .ok_or_else(|| Error::not_found("Post", slug))?;
+        let post = self.filter_content(post).await;
 
-        context.insert("post", &post);
+        let (pipeline, compare) = self.migration.plan(&post.post_type, slug).await;
+
+        if !compare {
+            if pipeline == RenderPipeline::Block {
+                match self.timed_block_render(&theme_id, &post).await {
+                    Ok(page) => return Ok(page),
+                    Err(e) => tracing::warn!(slug, "Block render failed, using classic: {}", e),
+                }
+            }
+            return self.timed_classic_render(&theme_id, &post).await;
+        }
+
+        let started = std::time::Instant::now();
+        let classic = self.timed_classic_render(&theme_id, &post).await?;
+        let classic_ms = started.elapsed().as_secs_f64() * 1000.0;
+
+        let started = std::time::Instant::now();
+        let block = self.timed_block_render(&theme_id, &post).await;
+        let block_ms = started.elapsed().as_secs_f64() * 1000.0;
+
+        let (block_html, block_error) = match &block {
+            Ok(page) => (page.html.as_str(), None),
+            Err(e) => ("", Some(e.to_string())),
+        };
+        let served = if pipeline == RenderPipeline::Block && block.is_ok() {
+            RenderPipeline::Block
+        } else {
+            RenderPipeline::Classic
+        };
+
+        self.migration
+            .record_comparison(RenderComparison {
+                slug: slug.to_string(),
+                post_type: post.post_type.clone(),
+                served,
+                classic_bytes: classic.html.len(),
+                block_bytes: block_html.len(),
+                classic_ms,
+                block_ms,
+                identical: classic.html == block_html,
+                diff: diff_lines(&classic.html, block_html),
+                block_error,
+                compared_at: Utc::now(),
+            })
+            .await;
+
+        match (served, block) {
+            (RenderPipeline::Block, Ok(page)) => Ok(page),
+            _ => Ok(classic),
+        }
+    }
+
+    async fn timed_classic_render(&self, theme_id: &str, post: &PostData) -> Result<RenderedPage> {
+        let started = std::time::Instant::now();
+        let result = self.render_post_classic(theme_id, post).await;
+        self.record_pipeline(RenderPipeline::Classic, &result, started)
+            .await;
+        result
+    }
+
+    async fn timed_block_render(&self, theme_id: &str, post: &PostData) -> Result<RenderedPage> {
+        let started = std::time::Instant::now();
+        let result = self.render_post_block(theme_id, post).await;
+        self.record_pipeline(RenderPipeline::Block, &result, started)
+            .await;
+        result
+    }
+
+    async fn record_pipeline(
+        &self,
+        pipeline: RenderPipeline,
+        result: &Result<RenderedPage>,
+        started: std::time::Instant,
+    ) {
+        let bytes = result.as_ref().map(|page| page.html.len()).unwrap_or(0);
+        self.migration
+            .record_render(pipeline, bytes, started.elapsed(), result.is_ok())
+            .await;
+    }
+
+    /// Render a post through the classic template hierarchy
+    async fn render_post_classic(&self, theme_id: &str, post: &PostData) -> Result<RenderedPage> {
+        let engine = self.get_engine(theme_id).await?;
+
+        let mut context = self.build_base_context(theme_id).await;
+        context.insert("post", post);
         context.insert("is_single", &true);
 
         // Build query context
         let query = QueryContext {
             is_single: true,
             post_type: Some(post.post_type.clone()),
-            post_slug: Some(slug.to_string()),
+            post_slug: Some(post.slug.clone()),
             post_id: None, // We use slug for template hierarchy instead
             ..Default::default()
         };
 
-        self.render_with_engine(&engine, &query, &context).await
+        let page = self
+            .render_with_engine(&engine, &query, &context, Some(&post.meta))
+            .await?;
+        let page = self
+            .apply_hreflang(page, &format!("/post/{}", post.slug), &post.meta)
+            .await;
+        Ok(page.for_post(post))
+    }
+
+    /// Render a post through the theme's block (FSE) templates
+    async fn render_post_block(&self, theme_id: &str, post: &PostData) -> Result<RenderedPage> {
+        let manager = self.get_fse_manager(theme_id).await?;
+
+        let specific = format!("single-{}", post.post_type);
+        let slug = [specific.as_str(), "single", "singular", "index"]
+            .into_iter()
+            .find(|slug| manager.get_template(slug).is_some())
+            .ok_or_else(|| {
+                Error::not_found("Block template", format!("single for {}", theme_id))
+            })?;
+
+        let markup = manager
+            .render(slug)
+            .map_err(|e| Error::internal(format!("Block template render error: {}", e)))?;
+
+        let site_info = self.site_info.read().await;
+        let html = markup
+            .replace(
+                "<!-- wp:post-title /-->",
+                &format!("<h1>{}</h1>", tera::escape_html(&post.title)),
+            )
+            .replace("<!-- wp:post-content /-->", &post.content)
+            .replace(
+                "<!-- wp:post-excerpt /-->",
+                post.excerpt.as_deref().unwrap_or_default(),
+            )
+            .replace(
+                "<!-- wp:site-title /-->",
+                &tera::escape_html(&site_info.name),
+            );
+        drop(site_info);
+        let html = self.apply_robots(html, Some(&post.meta)).await;
+        let html = self.apply_web_vitals(html).await;
+        let html = self.apply_responsive_images(html).await;
+
+        let page = RenderedPage {
+            html,
+            status_code: 200,
+            cache_control: "public, max-age=60".to_string(),
+            content_type: "text/html; charset=utf-8".to_string(),
+            surrogate_keys: Vec::new(),
+            cache_override: None,
+            content_flags: Vec::new(),
+        };
+        let page = self
+            .apply_hreflang(page, &format!("/post/{}", post.slug), &post.meta)
+            .await;
+        Ok(page.for_post(post))
+    }
+
+    /// Get or load the FSE template manager for a theme
+    async fn get_fse_manager(&self, theme_id: &str) -> Result<Arc<FseManager>> {
+        if let Some(manager) = self.fse_managers.read().await.get(theme_id) {
+            return Ok(manager.clone());
+        }
+
+        let theme_dir = self.themes_dir.join(theme_id);
+        let manager = FseManager::new(theme_dir.clone(), theme_dir.join("custom-templates"));
+        manager
+            .load_templates()
+            .await
+            .map_err(|e| Error::internal(format!("Failed to load block templates: {}", e)))?;
+        manager
+            .load_parts()
+            .await
+            .map_err(|e| Error::internal(format!("Failed to load template parts: {}", e)))?;
+
+        let manager = Arc::new(manager);
+        self.fse_managers
+            .write()
+            .await
+            .insert(theme_id.to_string(), manager.clone());
+        Ok(manager)
     }
 
     /// Render a page
@@ -666,6 +922,7 @@ impl RenderService {
             .load_page_by_slug(slug)
             .await?
             .ok_or_else(|| Error::not_found("Page", slug))?;
+        let page = self.filter_content(page).await;
 
         context.insert("page", &page);
         context.insert("post", &page); // WordPress uses 'post' for pages too
@@ -684,7 +941,13 @@ impl RenderService {
             ..Default::default()
         };
 
-        self.render_with_engine(&engine, &query, &context).await
+        let rendered = self
+            .render_with_engine(&engine, &query, &context, Some(&page.meta))
+            .await?;
+        let rendered = self
+            .apply_hreflang(rendered, &format!("/page/{}", slug), &page.meta)
+            .await;
+        Ok(rendered.for_post(&page))
     }
 
     /// Render category archive
@@ -694,159 +957,545 @@ impl RenderService {
         page: i32,
         preview_token: Option<&str>,
     ) -> Result<RenderedPage> {
+        self.render_archive(&ArchiveQuery::term("category", slug), page, preview_token)
+            .await
+    }
+
+    /// Render tag archive
+    pub async fn render_tag(
+        &self,
+        slug: &str,
+        page: i32,
+        preview_token: Option<&str>,
+    ) -> Result<RenderedPage> {
+        self.render_archive(&ArchiveQuery::term("tag", slug), page, preview_token)
+            .await
+    }
+
+    /// Render author archive
+    pub async fn render_author(
+        &self,
+        slug: &str,
+        page: i32,
+        preview_token: Option<&str>,
+    ) -> Result<RenderedPage> {
+        self.render_archive(&ArchiveQuery::Author(slug.to_string()), page, preview_token)
+            .await
+    }
+
+    /// Render any archive (term, term intersection, author or date).
+    ///
+    /// Pages past the last page are not found; an empty archive still
+    /// renders its first page.
+    pub async fn render_archive(
+        &self,
+        query: &ArchiveQuery,
+        page: i32,
+        preview_token: Option<&str>,
+    ) -> Result<RenderedPage> {
+        if page < 1 {
+            return Err(Error::not_found("Archive page", page.to_string()));
+        }
+
         let theme_id = self.get_active_theme_id(preview_token).await?;
         let engine = self.get_engine(&theme_id).await?;
 
         let mut context = self.build_base_context(&theme_id).await;
 
-        // Load category info
-        let category = self
-            .load_term_by_slug(slug, "category")
-            .await?
-            .ok_or_else(|| Error::not_found("Category", slug))?;
-
-        // Load posts in category
+        let resolved = self.resolve_archive(query).await?;
         let (posts, total) = self
-            .load_posts_by_term(&category.id, "category", page, 10)
+            .load_archive_posts(&resolved, page, ARCHIVE_PER_PAGE)
             .await?;
-        let pagination = self.build_pagination(page, total, 10, &format!("/category/{}", slug));
+        let pagination = self.build_pagination(page, total, ARCHIVE_PER_PAGE, &query.page_path(1));
+        if page > 1 && page > pagination.total_pages {
+            return Err(Error::not_found("Archive page", page.to_string()));
+        }
+
+        let site_url = self.site_info.read().await.url.clone();
+        let site_url = site_url.trim_end_matches('/');
 
-        context.insert("term", &category);
-        context.insert("category", &category);
+        resolved.insert_into(&mut context);
         context.insert("posts", &posts);
         context.insert("pagination", &pagination);
-        context.insert("is_category", &true);
         context.insert("is_archive", &true);
+        context.insert("is_paged", &(page > 1));
+        context.insert("feed_url", &format!("{}{}", site_url, query.feed_path()));
         context.insert(
-            "archive",
-            &ArchiveData {
-                title: category.name.clone(),
-                description: category.description.clone(),
-                archive_type: "category".to_string(),
-                term: Some(category.clone()),
-                author: None,
-                year: None,
-                month: None,
-                day: None,
-            },
+            "page",
+            &serde_json::json!({
+                "title": &resolved.archive.title,
+                "description": &resolved.archive.description,
+                "url": format!("{}{}", site_url, query.page_path(page)),
+                "canonical": format!("{}{}", site_url, pagination.canonical_url),
+                "prev": pagination.previous_url.as_ref().map(|url| format!("{}{}", site_url, url)),
+                "next": pagination.next_url.as_ref().map(|url| format!("{}{}", site_url, url)),
+            }),
         );
 
-        let query = QueryContext {
-            is_category: true,
-            is_archive: true,
-            term_slug: Some(slug.to_string()),
-            term_id: None, // We use slug for template hierarchy instead
-            taxonomy: Some("category".to_string()),
-            ..Default::default()
+        let mut rendered = self
+            .render_with_engine(&engine, &resolved.query, &context, None)
+            .await?;
+        if let Some(profile) = resolved.author.as_ref().and_then(|a| a.profile.as_ref()) {
+            rendered.html = inject_json_ld(&rendered.html, &profile.structured_data(site_url));
+        }
+        Ok(rendered.with_keys(resolved.surrogate_keys()))
+    }
+
+    /// Render the site feed or an archive feed.
+    ///
+    /// Themes can provide `feed-*` templates for RSS through the template
+    /// hierarchy; otherwise, and for Atom and JSON Feed, a built-in document
+    /// is produced.
+    pub async fn render_feed(
+        &self,
+        scope: &FeedScope,
+        format: FeedFormat,
+        preview_token: Option<&str>,
+    ) -> Result<RenderedFeed> {
+        let theme_id = self.get_active_theme_id(preview_token).await?;
+        let engine = self.get_engine(&theme_id).await?;
+
+        let (resolved, posts) = match scope {
+            FeedScope::Site => (None, self.load_recent_posts(FEED_SIZE).await?),
+            FeedScope::Archive(query) => {
+                let resolved = self.resolve_archive(query).await?;
+                let (posts, _) = self.load_archive_posts(&resolved, 1, FEED_SIZE).await?;
+                (Some(resolved), posts)
+            }
+        };
+        let mut query = match resolved {
+            Some(ref resolved) => resolved.query.clone(),
+            None => QueryContext {
+                is_home: true,
+                ..Default::default()
+            },
         };
+        query.is_feed = true;
 
-        self.render_with_engine(&engine, &query, &context).await
+        let feed_path = scope.feed_path(format);
+        let template = if format == FeedFormat::Rss {
+            engine.hierarchy().find_template(&query)
+        } else {
+            None
+        };
+        let html = if template.is_some() {
+            let mut context = self.build_base_context(&theme_id).await;
+            if let Some(ref resolved) = resolved {
+                resolved.insert_into(&mut context);
+            }
+            context.insert("posts", &posts);
+            context.insert("archive_url", &scope.page_path());
+            context.insert("feed_url", &feed_path);
+            engine
+                .render_for_query(&query, &context)
+                .map_err(|e| Error::internal(format!("Template render error: {}", e)))?
+        } else {
+            let site_info = self.site_info.read().await;
+            let (title, description) = match resolved {
+                Some(ref resolved) => (
+                    format!("{} - {}", resolved.archive.title, site_info.name),
+                    resolved
+                        .archive
+                        .description
+                        .clone()
+                        .unwrap_or_else(|| site_info.description.clone()),
+                ),
+                None => (site_info.name.clone(), site_info.description.clone()),
+            };
+            let channel = FeedChannel {
+                site: &site_info,
+                title,
+                description,
+                link: scope.page_path(),
+                self_link: feed_path,
+            };
+            render_feed(format, &channel, &posts)
+        };
+
+        let surrogate_keys = match resolved {
+            Some(ref resolved) => resolved.surrogate_keys(),
+            None => SurrogateKeys::new()
+                .post_type("post")
+                .post_type(EPISODE_POST_TYPE),
+        };
+        Ok(RenderedFeed {
+            page: RenderedPage {
+                html,
+                status_code: 200,
+                cache_control: "public, max-age=300".to_string(),
+                content_type: format.content_type().to_string(),
+                surrogate_keys: surrogate_keys.build(),
+                cache_override: None,
+                content_flags: Vec::new(),
+            },
+            last_modified: last_modified(&posts),
+        })
     }
 
-    /// Render tag archive
-    pub async fn render_tag(
+    /// Render the podcast feed (`/podcast/feed`) from the latest episodes
+    pub async fn render_podcast_feed(&self, show: &PodcastConfig) -> Result<RenderedFeed> {
+        let posts = self
+            .load_recent_of_types(&[EPISODE_POST_TYPE], PODCAST_FEED_SIZE)
+            .await?;
+
+        let site_info = self.site_info.read().await;
+        let channel = FeedChannel {
+            site: &site_info,
+            title: show.title.clone().unwrap_or_else(|| site_info.name.clone()),
+            description: show
+                .description
+                .clone()
+                .unwrap_or_else(|| site_info.description.clone()),
+            link: "/".to_string(),
+            self_link: "/podcast/feed".to_string(),
+        };
+        let html = render_podcast_rss(&channel, show, &posts);
+        drop(site_info);
+
+        Ok(RenderedFeed {
+            page: RenderedPage {
+                html,
+                status_code: 200,
+                cache_control: "public, max-age=300".to_string(),
+                content_type: FeedFormat::Rss.content_type().to_string(),
+                surrogate_keys: SurrogateKeys::new().post_type(EPISODE_POST_TYPE).build(),
+                cache_override: None,
+                content_flags: Vec::new(),
+            },
+            last_modified: last_modified(&posts),
+        })
+    }
+
+    /// Render a single event (`/event/{slug}`) with schema.org data for its
+    /// next occurrence
+    pub async fn render_event(
         &self,
         slug: &str,
-        page: i32,
         preview_token: Option<&str>,
     ) -> Result<RenderedPage> {
         let theme_id = self.get_active_theme_id(preview_token).await?;
         let engine = self.get_engine(&theme_id).await?;
 
-        let mut context = self.build_base_context(&theme_id).await;
-
-        // Load tag info
-        let tag = self
-            .load_term_by_slug(slug, "tag")
+        let post = self
+            .load_event_by_slug(slug)
             .await?
-            .ok_or_else(|| Error::not_found("Tag", slug))?;
+            .ok_or_else(|| Error::not_found("Event", slug))?;
+        let post = self.filter_content(post).await;
 
-        // Load posts with tag
-        let (posts, total) = self.load_posts_by_term(&tag.id, "tag", page, 10).await?;
-        let pagination = self.build_pagination(page, total, 10, &format!("/tag/{}", slug));
-
-        context.insert("term", &tag);
-        context.insert("tag", &tag);
-        context.insert("posts", &posts);
-        context.insert("pagination", &pagination);
-        context.insert("is_tag", &true);
-        context.insert("is_archive", &true);
-        context.insert(
-            "archive",
-            &ArchiveData {
-                title: tag.name.clone(),
-                description: tag.description.clone(),
-                archive_type: "tag".to_string(),
-                term: Some(tag.clone()),
-                author: None,
-                year: None,
-                month: None,
-                day: None,
-            },
-        );
+        let mut context = self.build_base_context(&theme_id).await;
+        context.insert("post", &post);
+        context.insert("is_single", &true);
 
         let query = QueryContext {
-            is_tag: true,
-            is_archive: true,
-            term_slug: Some(slug.to_string()),
-            term_id: None, // We use slug for template hierarchy instead
-            taxonomy: Some("post_tag".to_string()),
+            is_single: true,
+            post_type: Some(EVENT_POST_TYPE.to_string()),
+            post_slug: Some(post.slug.clone()),
             ..Default::default()
         };
 
-        self.render_with_engine(&engine, &query, &context).await
+        let path = format!("/event/{}", post.slug);
+        let mut page = self
+            .render_with_engine(&engine, &query, &context, Some(&post.meta))
+            .await?;
+        if let Some(ref event) = post.event {
+            let url = format!("{}{}", self.site_url().await.trim_end_matches('/'), path);
+            page.html = inject_json_ld(&page.html, &event_json_ld(&post, event, &url));
+        }
+        let page = self.apply_hreflang(page, &path, &post.meta).await;
+        Ok(page.for_post(&post))
     }
 
-    /// Render author archive
-    pub async fn render_author(
+    /// Render the events archive: upcoming and running events by their next
+    /// occurrence (`/events`), or past events, latest first (`/events/past`)
+    pub async fn render_events(
         &self,
-        slug: &str,
+        past: bool,
         page: i32,
         preview_token: Option<&str>,
     ) -> Result<RenderedPage> {
+        if page < 1 {
+            return Err(Error::not_found("Events page", page.to_string()));
+        }
+
         let theme_id = self.get_active_theme_id(preview_token).await?;
         let engine = self.get_engine(&theme_id).await?;
 
-        let mut context = self.build_base_context(&theme_id).await;
+        let now = Utc::now();
+        let offset = i64::from(page - 1) * i64::from(ARCHIVE_PER_PAGE);
+        let (ids, total) = if past {
+            let (events, total) =
+                Event::ended(&self.pool, now, i64::from(ARCHIVE_PER_PAGE), offset).await?;
+            let ids: Vec<Uuid> = events.iter().map(|e| e.event.post_id).collect();
+            (ids, total)
+        } else {
+            let mut upcoming: Vec<(DateTime<Utc>, Uuid)> =
+                Event::overlapping(&self.pool, now, None, true)
+                    .await?
+                    .iter()
+                    .filter_map(|e| {
+                        let next = e.event.schedule().next_occurrence(now)?;
+                        Some((next.starts_at, e.event.post_id))
+                    })
+                    .collect();
+            upcoming.sort();
+            let total = upcoming.len() as i64;
+            let ids = upcoming
+                .into_iter()
+                .skip(offset as usize)
+                .take(ARCHIVE_PER_PAGE as usize)
+                .map(|(_, id)| id)
+                .collect();
+            (ids, total)
+        };
+        let posts = self.load_events_by_ids(&ids).await?;
 
-        // Load author info
-        let author = self
-            .load_author_by_slug(slug)
-            .await?
-            .ok_or_else(|| Error::not_found("Author", slug))?;
+        let base_path = if past { "/events/past" } else { "/events" };
+        let pagination = self.build_pagination(page, total, ARCHIVE_PER_PAGE, base_path);
+        if page > 1 && page > pagination.total_pages {
+            return Err(Error::not_found("Events page", page.to_string()));
+        }
 
-        // Load posts by author
-        let (posts, total) = self.load_posts_by_author(&author.id, page, 10).await?;
-        let pagination = self.build_pagination(page, total, 10, &format!("/author/{}", slug));
+        let site_url = self.site_info.read().await.url.clone();
+        let site_url = site_url.trim_end_matches('/');
 
-        context.insert("author", &author);
+        let mut context = self.build_base_context(&theme_id).await;
         context.insert("posts", &posts);
         context.insert("pagination", &pagination);
-        context.insert("is_author", &true);
         context.insert("is_archive", &true);
+        context.insert("is_paged", &(page > 1));
+        context.insert("events_scope", if past { "past" } else { "upcoming" });
+        context.insert("ical_url", &format!("{}/events.ics", site_url));
         context.insert(
-            "archive",
-            &ArchiveData {
-                title: author.name.clone(),
-                description: author.bio.clone(),
-                archive_type: "author".to_string(),
-                term: None,
-                author: Some(author.clone()),
-                year: None,
-                month: None,
-                day: None,
-            },
+            "page",
+            &serde_json::json!({
+                "title": if past { "Past events" } else { "Events" },
+                "url": format!("{}{}", site_url, pagination.canonical_url),
+                "canonical": format!("{}{}", site_url, pagination.canonical_url),
+                "prev": pagination.previous_url.as_ref().map(|url| format!("{}{}", site_url, url)),
+                "next": pagination.next_url.as_ref().map(|url| format!("{}{}", site_url, url)),
+            }),
         );
 
         let query = QueryContext {
-            is_author: true,
             is_archive: true,
-            author_slug: Some(slug.to_string()),
-            author_id: None, // We use slug for template hierarchy instead
+            post_type: Some(EVENT_POST_TYPE.to_string()),
             ..Default::default()
         };
+        let rendered = self
+            .render_with_engine(&engine, &query, &context, None)
+            .await?;
+        Ok(rendered.with_keys(SurrogateKeys::new().post_type(EVENT_POST_TYPE)))
+    }
+
+    /// Render the iCalendar feed (`/events.ics`) of published events that
+    /// are still running or ended within the last year
+    pub async fn render_events_ical(&self) -> Result<RenderedFeed> {
+        let since = Utc::now() - Duration::days(ICAL_HISTORY_DAYS);
+        let events = Event::overlapping(&self.pool, since, None, true).await?;
+        let ids: Vec<Uuid> = events.iter().map(|e| e.event.post_id).collect();
+        let posts = self.load_events_by_ids(&ids).await?;
 
-        self.render_with_engine(&engine, &query, &context).await
+        let site_info = self.site_info.read().await;
+        let site_url = site_info.url.trim_end_matches('/');
+        let host = reqwest::Url::parse(site_url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+            .unwrap_or_else(|| "localhost".to_string());
+        let events: HashMap<String, &Event> = events
+            .iter()
+            .map(|e| (e.event.post_id.to_string(), &e.event))
+            .collect();
+        let entries: Vec<CalendarEntry> = posts
+            .iter()
+            .filter_map(|post| {
+                Some(CalendarEntry {
+                    post,
+                    event: events.get(&post.id)?,
+                    url: format!("{}/event/{}", site_url, post.slug),
+                })
+            })
+            .collect();
+        let html = render_ical(&site_info.name, &host, &entries);
+        drop(site_info);
+
+        Ok(RenderedFeed {
+            page: RenderedPage {
+                html,
+                status_code: 200,
+                cache_control: "public, max-age=300".to_string(),
+                content_type: "text/calendar; charset=utf-8".to_string(),
+                surrogate_keys: SurrogateKeys::new().post_type(EVENT_POST_TYPE).build(),
+                cache_override: None,
+                content_flags: Vec::new(),
+            },
+            last_modified: last_modified(&posts),
+        })
+    }
+
+    /// Load the terms, author or date range behind an archive query
+    async fn resolve_archive(&self, query: &ArchiveQuery) -> Result<ResolvedArchive> {
+        match query {
+            ArchiveQuery::Terms(refs) => {
+                // URLs name taxonomies by route (`/tag/...`); terms are
+                // looked up in the registered taxonomy behind it
+                let mut taxonomies: HashMap<&str, Taxonomy> = HashMap::new();
+                let mut terms = Vec::with_capacity(refs.len());
+                for term in refs {
+                    if !taxonomies.contains_key(term.taxonomy.as_str()) {
+                        let taxonomy = Taxonomy::by_route(&self.pool, &term.taxonomy)
+                            .await?
+                            .ok_or_else(|| Error::not_found("Taxonomy", &term.taxonomy))?;
+                        taxonomies.insert(&term.taxonomy, taxonomy);
+                    }
+                    let taxonomy = &taxonomies[term.taxonomy.as_str()].slug;
+                    let data = self
+                        .load_term_by_slug(&term.slug, taxonomy)
+                        .await?
+                        .ok_or_else(|| Error::not_found(term_label(&term.taxonomy), &term.slug))?;
+                    terms.push(data);
+                }
+
+                let primary = query.primary_taxonomy().unwrap_or("category").to_string();
+                let (taxonomy, post_types) = taxonomies
+                    .remove(primary.as_str())
+                    .map(|t| (t.slug, t.post_types.0))
+                    .unwrap_or_else(|| (CATEGORY_TAXONOMY.to_string(), vec!["post".to_string()]));
+                let single = terms.len() == 1;
+                let title = terms
+                    .iter()
+                    .map(|t| t.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(" & ");
+
+                Ok(ResolvedArchive {
+                    archive: ArchiveData {
+                        title,
+                        description: if single {
+                            terms[0].description.clone()
+                        } else {
+                            None
+                        },
+                        archive_type: primary.clone(),
+                        term: terms.first().cloned(),
+                        author: None,
+                        year: None,
+                        month: None,
+                        day: None,
+                    },
+                    query: QueryContext {
+                        is_category: taxonomy == CATEGORY_TAXONOMY,
+                        is_tag: taxonomy == TAG_TAXONOMY,
+                        is_tax: taxonomy != CATEGORY_TAXONOMY && taxonomy != TAG_TAXONOMY,
+                        is_archive: true,
+                        // Intersections use the generic taxonomy templates
+                        term_slug: single.then(|| terms[0].slug.clone()),
+                        term_id: None, // We use slug for template hierarchy instead
+                        taxonomy: Some(taxonomy),
+                        ..Default::default()
+                    },
+                    terms,
+                    post_types,
+                    author: None,
+                    date: None,
+                })
+            }
+            ArchiveQuery::Author(slug) => {
+                let mut author = self
+                    .load_author_by_slug(slug)
+                    .await?
+                    .ok_or_else(|| Error::not_found("Author", slug))?;
+                if let Some(ref profiles) = self.profiles {
+                    author.profile = profiles
+                        .view_by_username(slug, ProfileViewer::Anonymous)
+                        .await?;
+                }
+
+                Ok(ResolvedArchive {
+                    archive: ArchiveData {
+                        title: author.name.clone(),
+                        description: author.bio.clone(),
+                        archive_type: "author".to_string(),
+                        term: None,
+                        author: Some(author.clone()),
+                        year: None,
+                        month: None,
+                        day: None,
+                    },
+                    query: QueryContext {
+                        is_author: true,
+                        is_archive: true,
+                        author_slug: Some(slug.clone()),
+                        author_id: None, // We use slug for template hierarchy instead
+                        ..Default::default()
+                    },
+                    terms: Vec::new(),
+                    post_types: Vec::new(),
+                    author: Some(author),
+                    date: None,
+                })
+            }
+            ArchiveQuery::Date(date) => Ok(ResolvedArchive {
+                archive: ArchiveData {
+                    title: date.title(),
+                    description: None,
+                    archive_type: "date".to_string(),
+                    term: None,
+                    author: None,
+                    year: Some(date.year),
+                    month: Some(date.month as i32),
+                    day: date.day.map(|d| d as i32),
+                },
+                query: QueryContext {
+                    is_date: true,
+                    is_archive: true,
+                    year: Some(date.year),
+                    month: Some(date.month),
+                    day: date.day,
+                    ..Default::default()
+                },
+                terms: Vec::new(),
+                post_types: Vec::new(),
+                author: None,
+                date: Some(*date),
+            }),
+        }
+    }
+
+    /// Load one page of posts for a resolved archive
+    async fn load_archive_posts(
+        &self,
+        resolved: &ResolvedArchive,
+        page: i32,
+        per_page: i32,
+    ) -> Result<(Vec<PostData>, i64)> {
+        if let Some(ref author) = resolved.author {
+            return self.load_posts_by_author(&author.id, page, per_page).await;
+        }
+        if let Some(date) = resolved.date {
+            let (start, end) = date.range();
+            return self.load_posts_by_date(start, end, page, per_page).await;
+        }
+
+        let term_ids = resolved
+            .terms
+            .iter()
+            .map(|t| Uuid::parse_str(&t.id))
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|e| Error::validation(format!("Invalid term ID: {}", e)))?;
+        // A term of a hierarchical taxonomy also lists its descendants' posts
+        let (groups, ids): (Vec<Uuid>, Vec<Uuid>) = descendant_ids(&self.pool, &term_ids)
+            .await?
+            .into_iter()
+            .unzip();
+        self.load_posts_by_terms(
+            &groups,
+            &ids,
+            term_ids.len(),
+            &resolved.post_types,
+            page,
+            per_page,
+        )
+        .await
     }
 
     /// Render search results
@@ -881,7 +1530,8 @@ impl RenderService {
             ..Default::default()
         };
 
-        self.render_with_engine(&engine, &query, &context).await
+        self.render_with_engine(&engine, &query, &context, None)
+            .await
     }
 
     /// Render 404 page
@@ -897,11 +1547,43 @@ impl RenderService {
             ..Default::default()
         };
 
-        let mut result = self.render_with_engine(&engine, &query, &context).await?;
+        let mut result = self
+            .render_with_engine(&engine, &query, &context, None)
+            .await?;
         result.status_code = 404;
         Ok(result)
     }
 
+    /// Name of the classic template the active theme uses for a post or page
+    pub async fn template_for(&self, post_type: &str, slug: &str) -> Result<Option<String>> {
+        let theme_id = self.get_active_theme_id(None).await?;
+        let engine = self.get_engine(&theme_id).await?;
+
+        let query = if post_type == "page" {
+            let page_template = self.load_page_by_slug(slug).await?.and_then(|page| {
+                page.meta
+                    .get("_wp_page_template")
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.to_string())
+            });
+            QueryContext {
+                is_page: true,
+                post_slug: Some(slug.to_string()),
+                page_template,
+                ..Default::default()
+            }
+        } else {
+            QueryContext {
+                is_single: true,
+                post_type: Some(post_type.to_string()),
+                post_slug: Some(slug.to_string()),
+                ..Default::default()
+            }
+        };
+
+        Ok(engine.hierarchy().find_template(&query).map(|t| t.name))
+    }
+
     /// Get active theme ID (or preview theme if token provided)
     async fn get_active_theme_id(&self, preview_token: Option<&str>) -> Result<String> {
         // Check if preview token is provided and valid
@@ -943,20 +1625,30 @@ impl RenderService {
         engine: &TemplateEngine,
         query: &QueryContext,
         context: &Context,
+        post_meta: Option<&HashMap<String, serde_json::Value>>,
     ) -> Result<RenderedPage> {
         let html = engine
             .render_for_query(query, context)
             .map_err(|e| Error::internal(format!("Template render error: {}", e)))?;
+        let html = self.apply_robots(html, post_meta).await;
+        let html = self.apply_web_vitals(html).await;
+        let html = self.apply_responsive_images(html).await;
 
         Ok(RenderedPage {
             html,
             status_code: 200,
             cache_control: "public, max-age=60".to_string(),
             content_type: "text/html; charset=utf-8".to_string(),
+            surrogate_keys: Vec::new(),
+            cache_override: None,
+            content_flags: Vec::new(),
         })
     }
 
-    /// Build pagination data
+    /// Build pagination data.
+    ///
+    /// Page URLs are canonical: the first page is always `base_url` itself,
+    /// never `?page=1`.
     fn build_pagination(
         &self,
         current: i32,
@@ -966,6 +1658,13 @@ impl RenderService {
     ) -> PaginationData {
         let total_pages = ((total as f64) / (per_page as f64)).ceil() as i32;
         let separator = if base_url.contains('?') { "&" } else { "?" };
+        let page_url = |page: i32| {
+            if page <= 1 {
+                base_url.to_string()
+            } else {
+                format!("{}{}page={}", base_url, separator, page)
+            }
+        };
 
         PaginationData {
             current_page: current,
@@ -975,15 +1674,16 @@ impl RenderService {
             has_previous: current > 1,
             has_next: current < total_pages,
             previous_url: if current > 1 {
-                Some(format!("{}{}page={}", base_url, separator, current - 1))
+                Some(page_url(current - 1))
             } else {
                 None
             },
             next_url: if current < total_pages {
-                Some(format!("{}{}page={}", base_url, separator, current + 1))
+                Some(page_url(current + 1))
             } else {
                 None
             },
+            canonical_url: page_url(current),
         }
     }
 
@@ -992,21 +1692,29 @@ impl RenderService {
     // ============================================================================
 
     async fn load_recent_posts(&self, limit: i32) -> Result<Vec<PostData>> {
-        let rows = sqlx::query_as::<_, PostRow>(
+        self.load_recent_of_types(&["post", EPISODE_POST_TYPE], limit)
+            .await
+    }
+
+    async fn load_recent_of_types(&self, post_types: &[&str], limit: i32) -> Result<Vec<PostData>> {
+        let rows = sqlx::query_as::<_, PostRow>(&format!(
             r#"
-            SELECT p.id, p.title, p.slug, p.content, p.excerpt, p.post_type::text, p.status::text,
+            SELECT p.id, p.short_id, p.title, p.slug, p.content, p.excerpt, p.post_type::text, p.status::text,
                    p.author_id, p.featured_image_id AS featured_media_id, p.created_at, p.updated_at, p.published_at,
                    u.display_name as author_name, u.username as author_slug,
                    u.bio as author_bio, u.avatar_url as author_avatar,
                    (SELECT COUNT(*) FROM comments c WHERE c.post_id = p.id AND c.status = 'approved') as comment_count
             FROM posts p
             JOIN users u ON p.author_id = u.id
-            WHERE p.status = 'published' AND p.post_type = 'post' AND p.deleted_at IS NULL
+            WHERE p.status = 'published' AND p.post_type = ANY($2) AND p.deleted_at IS NULL
+              AND {site}
             ORDER BY p.published_at DESC NULLS LAST
             LIMIT $1
-            "#
-        )
+            "#,
+            site = site_filter("p.site_id")
+        ))
         .bind(limit)
+        .bind(post_types)
         .fetch_all(&self.pool)
         .await
         .map_err(|e| Error::database_with_source("Failed to load posts", e))?;
@@ -1020,18 +1728,20 @@ impl RenderService {
     }
 
     async fn load_post_by_slug(&self, slug: &str) -> Result<Option<PostData>> {
-        let row = sqlx::query_as::<_, PostRow>(
+        let row = sqlx::query_as::<_, PostRow>(&format!(
             r#"
-            SELECT p.id, p.title, p.slug, p.content, p.excerpt, p.post_type::text, p.status::text,
+            SELECT p.id, p.short_id, p.title, p.slug, p.content, p.excerpt, p.post_type::text, p.status::text,
                    p.author_id, p.featured_image_id AS featured_media_id, p.created_at, p.updated_at, p.published_at,
                    u.display_name as author_name, u.username as author_slug,
                    u.bio as author_bio, u.avatar_url as author_avatar,
                    (SELECT COUNT(*) FROM comments c WHERE c.post_id = p.id AND c.status = 'approved') as comment_count
             FROM posts p
             JOIN users u ON p.author_id = u.id
-            WHERE p.slug = $1 AND p.post_type = 'post' AND p.status = 'published' AND p.deleted_at IS NULL
-            "#
-        )
+            WHERE p.slug = $1 AND p.post_type IN ('post', 'episode') AND p.status = 'published' AND p.deleted_at IS NULL
+              AND {site}
+            "#,
+            site = site_filter("p.site_id")
+        ))
         .bind(slug)
         .fetch_optional(&self.pool)
         .await
@@ -1044,9 +1754,9 @@ impl RenderService {
     }
 
     async fn load_page_by_slug(&self, slug: &str) -> Result<Option<PostData>> {
-        let row = sqlx::query_as::<_, PostRow>(
+        let row = sqlx::query_as::<_, PostRow>(&format!(
             r#"
-            SELECT p.id, p.title, p.slug, p.content, p.excerpt, p.post_type::text, p.status::text,
+            SELECT p.id, p.short_id, p.title, p.slug, p.content, p.excerpt, p.post_type::text, p.status::text,
                    p.author_id, p.featured_image_id AS featured_media_id, p.created_at, p.updated_at, p.published_at,
                    u.display_name as author_name, u.username as author_slug,
                    u.bio as author_bio, u.avatar_url as author_avatar,
@@ -1054,8 +1764,10 @@ impl RenderService {
             FROM posts p
             JOIN users u ON p.author_id = u.id
             WHERE p.slug = $1 AND p.post_type = 'page' AND p.status = 'published' AND p.deleted_at IS NULL
-            "#
-        )
+              AND {site}
+            "#,
+            site = site_filter("p.site_id")
+        ))
         .bind(slug)
         .fetch_optional(&self.pool)
         .await
@@ -1067,18 +1779,78 @@ impl RenderService {
         }
     }
 
+    async fn load_event_by_slug(&self, slug: &str) -> Result<Option<PostData>> {
+        let row = sqlx::query_as::<_, PostRow>(&format!(
+            r#"
+            SELECT p.id, p.short_id, p.title, p.slug, p.content, p.excerpt, p.post_type::text, p.status::text,
+                   p.author_id, p.featured_image_id AS featured_media_id, p.created_at, p.updated_at, p.published_at,
+                   u.display_name as author_name, u.username as author_slug,
+                   u.bio as author_bio, u.avatar_url as author_avatar,
+                   (SELECT COUNT(*) FROM comments c WHERE c.post_id = p.id AND c.status = 'approved') as comment_count
+            FROM posts p
+            JOIN users u ON p.author_id = u.id
+            WHERE p.slug = $1 AND p.post_type = 'event' AND p.status = 'published' AND p.deleted_at IS NULL
+              AND {site}
+            "#,
+            site = site_filter("p.site_id")
+        ))
+        .bind(slug)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load event", e))?;
+
+        match row {
+            Some(r) => Ok(Some(self.row_to_post_data(r).await?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Published events with the given ids, in the order given; ids that
+    /// are not published events are left out
+    async fn load_events_by_ids(&self, ids: &[Uuid]) -> Result<Vec<PostData>> {
+        let rows = sqlx::query_as::<_, PostRow>(&format!(
+            r#"
+            SELECT p.id, p.short_id, p.title, p.slug, p.content, p.excerpt, p.post_type::text, p.status::text,
+                   p.author_id, p.featured_image_id AS featured_media_id, p.created_at, p.updated_at, p.published_at,
+                   u.display_name as author_name, u.username as author_slug,
+                   u.bio as author_bio, u.avatar_url as author_avatar,
+                   (SELECT COUNT(*) FROM comments c WHERE c.post_id = p.id AND c.status = 'approved') as comment_count
+            FROM posts p
+            JOIN users u ON p.author_id = u.id
+            WHERE p.id = ANY($1) AND p.post_type = 'event' AND p.status = 'published' AND p.deleted_at IS NULL
+              AND {site}
+            "#,
+            site = site_filter("p.site_id")
+        ))
+        .bind(ids)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load events", e))?;
+
+        let mut rows: HashMap<Uuid, PostRow> = rows.into_iter().map(|row| (row.id, row)).collect();
+        let mut posts = Vec::with_capacity(ids.len());
+        for id in ids {
+            if let Some(row) = rows.remove(id) {
+                posts.push(self.row_to_post_data(row).await?);
+            }
+        }
+        Ok(posts)
+    }
+
     async fn load_term_by_slug(&self, slug: &str, taxonomy: &str) -> Result<Option<TermData>> {
-        let row = sqlx::query_as::<_, TermRow>(
+        let row = sqlx::query_as::<_, TermRow>(&format!(
             r#"
             SELECT t.id, t.name, t.slug, t.description, tx.slug as taxonomy,
-                   (SELECT COUNT(DISTINCT pt.post_id) FROM post_terms pt
-                    JOIN posts p ON pt.post_id = p.id
-                    WHERE pt.term_id = t.id AND p.status = 'published') as count
+                   (SELECT COUNT(DISTINCT tr.object_id) FROM term_relationships tr
+                    JOIN posts p ON tr.object_id = p.id
+                    WHERE tr.term_id = t.id AND tr.object_type = 'post'
+                      AND p.status = 'published' AND {site}) as count
             FROM terms t
             JOIN taxonomies tx ON tx.id = t.taxonomy_id
             WHERE t.slug = $1 AND tx.slug = $2
             "#,
-        )
+            site = site_filter("p.site_id")
+        ))
         .bind(slug)
         .bind(taxonomy)
         .fetch_optional(&self.pool)
@@ -1095,48 +1867,132 @@ impl RenderService {
         }))
     }
 
-    async fn load_posts_by_term(
+    /// Posts of the given types carrying a term of every group. Groups are
+    /// given as parallel lists of group and term ids.
+    async fn load_posts_by_terms(
         &self,
-        term_id_str: &str,
-        _taxonomy: &str,
+        groups: &[Uuid],
+        term_ids: &[Uuid],
+        group_count: usize,
+        post_types: &[String],
         page: i32,
         per_page: i32,
     ) -> Result<(Vec<PostData>, i64)> {
-        let term_id = Uuid::parse_str(term_id_str)
-            .map_err(|e| Error::validation(format!("Invalid term ID: {}", e)))?;
         let offset = (page - 1) * per_page;
 
         // Get total count
-        let count: (i64,) = sqlx::query_as(
+        let count: (i64,) = sqlx::query_as(&format!(
             r#"
-            SELECT COUNT(DISTINCT p.id)
+            SELECT COUNT(*)
             FROM posts p
-            JOIN post_terms pt ON pt.post_id = p.id
-            WHERE pt.term_id = $1 AND p.status = 'published' AND p.post_type = 'post' AND p.deleted_at IS NULL
-            "#
-        )
-        .bind(term_id)
+            WHERE p.id IN (
+                SELECT tr.object_id FROM term_relationships tr
+                JOIN unnest($1::uuid[], $2::uuid[]) AS g(grp, term_id) ON g.term_id = tr.term_id
+                WHERE tr.object_type = 'post'
+                GROUP BY tr.object_id
+                HAVING COUNT(DISTINCT g.grp) = $3
+            )
+            AND p.status = 'published' AND p.post_type = ANY($4) AND p.deleted_at IS NULL AND {site}
+            "#,
+            site = site_filter("p.site_id")
+        ))
+        .bind(groups)
+        .bind(term_ids)
+        .bind(group_count as i64)
+        .bind(post_types)
         .fetch_one(&self.pool)
         .await
         .map_err(|e| Error::database_with_source("Failed to count posts", e))?;
 
         // Get posts
-        let rows = sqlx::query_as::<_, PostRow>(
+        let rows = sqlx::query_as::<_, PostRow>(&format!(
             r#"
-            SELECT p.id, p.title, p.slug, p.content, p.excerpt, p.post_type::text, p.status::text,
+            SELECT p.id, p.short_id, p.title, p.slug, p.content, p.excerpt, p.post_type::text, p.status::text,
                    p.author_id, p.featured_image_id AS featured_media_id, p.created_at, p.updated_at, p.published_at,
                    u.display_name as author_name, u.username as author_slug,
                    u.bio as author_bio, u.avatar_url as author_avatar,
                    (SELECT COUNT(*) FROM comments c WHERE c.post_id = p.id AND c.status = 'approved') as comment_count
             FROM posts p
             JOIN users u ON p.author_id = u.id
-            JOIN post_terms pt ON pt.post_id = p.id
-            WHERE pt.term_id = $1 AND p.status = 'published' AND p.post_type = 'post' AND p.deleted_at IS NULL
+            WHERE p.id IN (
+                SELECT tr.object_id FROM term_relationships tr
+                JOIN unnest($1::uuid[], $2::uuid[]) AS g(grp, term_id) ON g.term_id = tr.term_id
+                WHERE tr.object_type = 'post'
+                GROUP BY tr.object_id
+                HAVING COUNT(DISTINCT g.grp) = $3
+            )
+            AND p.status = 'published' AND p.post_type = ANY($4) AND p.deleted_at IS NULL AND {site}
             ORDER BY p.published_at DESC NULLS LAST
-            LIMIT $2 OFFSET $3
-            "#
-        )
-        .bind(term_id)
+            LIMIT $5 OFFSET $6
+            "#,
+            site = site_filter("p.site_id")
+        ))
+        .bind(groups)
+        .bind(term_ids)
+        .bind(group_count as i64)
+        .bind(post_types)
+        .bind(per_page)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load posts", e))?;
+
+        let mut posts = Vec::new();
+        for row in rows {
+            let post = self.row_to_post_data(row).await?;
+            posts.push(post);
+        }
+
+        Ok((posts, count.0))
+    }
+
+    /// Posts published within `[start, end)`
+    async fn load_posts_by_date(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        page: i32,
+        per_page: i32,
+    ) -> Result<(Vec<PostData>, i64)> {
+        let offset = (page - 1) * per_page;
+
+        // Get total count
+        let count: (i64,) = sqlx::query_as(&format!(
+            r#"
+            SELECT COUNT(*)
+            FROM posts
+            WHERE published_at >= $1 AND published_at < $2
+              AND status = 'published' AND post_type IN ('post', 'episode') AND deleted_at IS NULL
+              AND {site}
+            "#,
+            site = site_filter("site_id")
+        ))
+        .bind(start)
+        .bind(end)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to count posts", e))?;
+
+        // Get posts
+        let rows = sqlx::query_as::<_, PostRow>(&format!(
+            r#"
+            SELECT p.id, p.short_id, p.title, p.slug, p.content, p.excerpt, p.post_type::text, p.status::text,
+                   p.author_id, p.featured_image_id AS featured_media_id, p.created_at, p.updated_at, p.published_at,
+                   u.display_name as author_name, u.username as author_slug,
+                   u.bio as author_bio, u.avatar_url as author_avatar,
+                   (SELECT COUNT(*) FROM comments c WHERE c.post_id = p.id AND c.status = 'approved') as comment_count
+            FROM posts p
+            JOIN users u ON p.author_id = u.id
+            WHERE p.published_at >= $1 AND p.published_at < $2
+              AND p.status = 'published' AND p.post_type IN ('post', 'episode') AND p.deleted_at IS NULL
+              AND {site}
+            ORDER BY p.published_at DESC
+            LIMIT $3 OFFSET $4
+            "#,
+            site = site_filter("p.site_id")
+        ))
+        .bind(start)
+        .bind(end)
         .bind(per_page)
         .bind(offset)
         .fetch_all(&self.pool)
@@ -1170,8 +2026,13 @@ impl RenderService {
             name: r.name.unwrap_or_else(|| slug.to_string()),
             slug: r.slug,
             bio: r.bio,
-            avatar_url: r.avatar_url,
+            avatar_url: Some(avatar_url_for(
+                r.id,
+                r.avatar_url.as_deref(),
+                DEFAULT_AVATAR_SIZE,
+            )),
             url: r.url,
+            profile: None,
         }))
     }
 
@@ -1186,33 +2047,37 @@ impl RenderService {
         let offset = (page - 1) * per_page;
 
         // Get total count
-        let count: (i64,) = sqlx::query_as(
+        let count: (i64,) = sqlx::query_as(&format!(
             r#"
             SELECT COUNT(*)
             FROM posts
-            WHERE author_id = $1 AND status = 'published' AND post_type = 'post' AND deleted_at IS NULL
-            "#
-        )
+            WHERE author_id = $1 AND status = 'published' AND post_type IN ('post', 'episode') AND deleted_at IS NULL
+              AND {site}
+            "#,
+            site = site_filter("site_id")
+        ))
         .bind(author_id)
         .fetch_one(&self.pool)
         .await
         .map_err(|e| Error::database_with_source("Failed to count posts", e))?;
 
         // Get posts
-        let rows = sqlx::query_as::<_, PostRow>(
+        let rows = sqlx::query_as::<_, PostRow>(&format!(
             r#"
-            SELECT p.id, p.title, p.slug, p.content, p.excerpt, p.post_type::text, p.status::text,
+            SELECT p.id, p.short_id, p.title, p.slug, p.content, p.excerpt, p.post_type::text, p.status::text,
                    p.author_id, p.featured_image_id AS featured_media_id, p.created_at, p.updated_at, p.published_at,
                    u.display_name as author_name, u.username as author_slug,
                    u.bio as author_bio, u.avatar_url as author_avatar,
                    (SELECT COUNT(*) FROM comments c WHERE c.post_id = p.id AND c.status = 'approved') as comment_count
             FROM posts p
             JOIN users u ON p.author_id = u.id
-            WHERE p.author_id = $1 AND p.status = 'published' AND p.post_type = 'post' AND p.deleted_at IS NULL
+            WHERE p.author_id = $1 AND p.status = 'published' AND p.post_type IN ('post', 'episode') AND p.deleted_at IS NULL
+              AND {site}
             ORDER BY p.published_at DESC NULLS LAST
             LIMIT $2 OFFSET $3
-            "#
-        )
+            "#,
+            site = site_filter("p.site_id")
+        ))
         .bind(author_id)
         .bind(per_page)
         .bind(offset)
@@ -1239,23 +2104,25 @@ impl RenderService {
         let search_pattern = format!("%{}%", query);
 
         // Get total count
-        let count: (i64,) = sqlx::query_as(
+        let count: (i64,) = sqlx::query_as(&format!(
             r#"
             SELECT COUNT(*)
             FROM posts
             WHERE (title ILIKE $1 OR content ILIKE $1 OR excerpt ILIKE $1)
-              AND status = 'published' AND post_type = 'post' AND deleted_at IS NULL
+              AND status = 'published' AND post_type IN ('post', 'episode') AND deleted_at IS NULL
+              AND {site}
             "#,
-        )
+            site = site_filter("site_id")
+        ))
         .bind(&search_pattern)
         .fetch_one(&self.pool)
         .await
         .map_err(|e| Error::database_with_source("Failed to count search results", e))?;
 
         // Get posts
-        let rows = sqlx::query_as::<_, PostRow>(
+        let rows = sqlx::query_as::<_, PostRow>(&format!(
             r#"
-            SELECT p.id, p.title, p.slug, p.content, p.excerpt, p.post_type::text, p.status::text,
+            SELECT p.id, p.short_id, p.title, p.slug, p.content, p.excerpt, p.post_type::text, p.status::text,
                    p.author_id, p.featured_image_id AS featured_media_id, p.created_at, p.updated_at, p.published_at,
                    u.display_name as author_name, u.username as author_slug,
                    u.bio as author_bio, u.avatar_url as author_avatar,
@@ -1263,11 +2130,13 @@ impl RenderService {
             FROM posts p
             JOIN users u ON p.author_id = u.id
             WHERE (p.title ILIKE $1 OR p.content ILIKE $1 OR p.excerpt ILIKE $1)
-              AND p.status = 'published' AND p.post_type = 'post' AND p.deleted_at IS NULL
+              AND p.status = 'published' AND p.post_type IN ('post', 'episode') AND p.deleted_at IS NULL
+              AND {site}
             ORDER BY p.published_at DESC NULLS LAST
             LIMIT $2 OFFSET $3
-            "#
-        )
+            "#,
+            site = site_filter("p.site_id")
+        ))
         .bind(&search_pattern)
         .bind(per_page)
         .bind(offset)
@@ -1302,8 +2171,28 @@ impl RenderService {
         // Load post meta
         let meta = self.load_post_meta(row.id).await?;
 
+        // Load the enclosure of podcast episodes
+        let episode = if row.post_type == EPISODE_POST_TYPE {
+            Episode::load(&self.pool, row.id)
+                .await?
+                .map(|episode| EpisodeData::from(&episode))
+        } else {
+            None
+        };
+
+        // Load the schedule of events
+        let event = if row.post_type == EVENT_POST_TYPE {
+            Event::load(&self.pool, row.id)
+                .await?
+                .map(|event| EventData::new(&event, Utc::now()))
+        } else {
+            None
+        };
+
         Ok(PostData {
             id: row.id.to_string(),
+            short_id: row.short_id,
+            shortlink: None,
             title: row.title,
             slug: row.slug,
             content: row.content.unwrap_or_default(),
@@ -1315,8 +2204,13 @@ impl RenderService {
                 name: row.author_name.unwrap_or_else(|| "Unknown".to_string()),
                 slug: row.author_slug,
                 bio: row.author_bio,
-                avatar_url: row.author_avatar,
+                avatar_url: Some(avatar_url_for(
+                    row.author_id,
+                    row.author_avatar.as_deref(),
+                    DEFAULT_AVATAR_SIZE,
+                )),
                 url: None,
+                profile: None,
             },
             featured_image,
             categories,
@@ -1326,6 +2220,8 @@ impl RenderService {
             published_at: row.published_at,
             comment_count: row.comment_count.unwrap_or(0) as i32,
             meta,
+            episode,
+            event,
         })
     }
 
@@ -1334,11 +2230,12 @@ impl RenderService {
         let rows = sqlx::query_as::<_, TermRow>(
             r#"
             SELECT t.id, t.name, t.slug, t.description, tx.slug as taxonomy,
-                   (SELECT COUNT(*) FROM post_terms WHERE term_id = t.id) as count
+                   t.count::bigint as count
             FROM terms t
             JOIN taxonomies tx ON tx.id = t.taxonomy_id
-            JOIN post_terms pt ON pt.term_id = t.id
-            WHERE pt.post_id = $1 AND tx.slug = $2
+            JOIN term_relationships tr ON tr.term_id = t.id
+            WHERE tr.object_id = $1 AND tr.object_type = 'post' AND tx.slug = $2
+            ORDER BY tr.term_order
             "#,
         )
         .bind(post_id)
@@ -1362,13 +2259,14 @@ impl RenderService {
 
     /// Load media by ID
     async fn load_media(&self, media_id: Uuid) -> Result<Option<MediaData>> {
-        let row = sqlx::query_as::<_, MediaRow>(
+        let row = sqlx::query_as::<_, MediaRow>(&format!(
             r#"
             SELECT id, url, alt_text as alt, title, width, height, mime_type
             FROM media
-            WHERE id = $1 AND deleted_at IS NULL
+            WHERE id = $1 AND deleted_at IS NULL AND {site}
             "#,
-        )
+            site = site_filter("site_id")
+        ))
         .bind(media_id)
         .fetch_optional(&self.pool)
         .await
@@ -1401,11 +2299,13 @@ impl RenderService {
     pub async fn clear_theme_cache(&self, theme_id: &str) {
         let mut engines = self.template_engines.write().await;
         engines.remove(theme_id);
+        self.fse_managers.write().await.remove(theme_id);
     }
 
     /// Clear all template caches
     pub async fn clear_all_caches(&self) {
         let mut engines = self.template_engines.write().await;
         engines.clear();
+        self.fse_managers.write().await.clear();
     }
 }
@@ -0,0 +1,569 @@
+//! Responsive images
+//!
+//! Rendered pages are post-processed so images load without shifting the
+//! layout or holding up the first paint:
+//!
+//! - images from the media library get their `width` and `height`, read
+//!   from the file header the first time a page shows them when the
+//!   library does not know them yet
+//! - images below the first few get `loading="lazy"`, all of them
+//!   `decoding="async"`, and iframes `loading="lazy"`
+//! - `srcset` and `sizes` list a version of the image for each registered
+//!   [`ImageSize`] smaller than the original. Versions not generated yet
+//!   point at [`TRANSFORM_PATH`], which resizes the original to WebP on
+//!   first request, keeps it in storage and redirects to it; later renders
+//!   link the stored version directly
+
+use bytes::Bytes;
+use rustpress_core::error::{Error, Result};
+use rustpress_core::tenant::site_filter;
+use rustpress_storage::Storage;
+use rustpress_themes::images::{
+    image_sources, optimize_images, probe_dimensions, ImageSize, KnownImage, LoadingPolicy,
+    ResponsiveImageGenerator,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::{RwLock, Semaphore};
+use uuid::Uuid;
+
+use super::json_setting::JsonSetting;
+
+/// Settings key holding the responsive image configuration
+pub const RESPONSIVE_IMAGES_SETTINGS_KEY: &str = "responsive_images_config";
+
+/// Stored responsive image settings
+const RESPONSIVE_IMAGES_SETTING: JsonSetting<ResponsiveImagesConfig> = JsonSetting::new(
+    RESPONSIVE_IMAGES_SETTINGS_KEY,
+    "media",
+    "responsive image settings",
+);
+
+/// Where missing versions are generated, as `{TRANSFORM_PATH}/{id}/{size}`
+pub const TRANSFORM_PATH: &str = "/media/transform";
+
+/// Storage directory of generated versions
+const DERIVATIVES_DIRECTORY: &str = "sizes";
+
+/// Images of a page looked up in the media library
+const MAX_IMAGES_PER_PAGE: usize = 200;
+
+/// Images of unknown dimensions measured while rendering one page
+const MAX_PROBES_PER_PAGE: usize = 8;
+
+/// Largest original resized, in bytes
+const MAX_SOURCE_SIZE: usize = 50 * 1024 * 1024;
+
+/// Versions generated at the same time
+const MAX_CONCURRENT_RESIZES: usize = 2;
+
+/// Most images eagerly loaded at the top of a page
+const MAX_EAGER_IMAGES: usize = 20;
+
+/// Render-time image settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResponsiveImagesConfig {
+    /// Post-process rendered pages
+    pub enabled: bool,
+    #[serde(flatten)]
+    pub policy: LoadingPolicy,
+    /// Point `srcset` at the transformation endpoint for versions not
+    /// generated yet; otherwise only stored versions are listed
+    pub transform_missing: bool,
+}
+
+impl Default for ResponsiveImagesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            policy: LoadingPolicy::default(),
+            transform_missing: true,
+        }
+    }
+}
+
+impl ResponsiveImagesConfig {
+    /// Load the configuration from settings, falling back to defaults
+    pub async fn load(pool: &PgPool) -> Result<Self> {
+        RESPONSIVE_IMAGES_SETTING.load_or_default(pool).await
+    }
+
+    /// Persist the configuration to settings
+    pub async fn save(&self, pool: &PgPool) -> Result<()> {
+        RESPONSIVE_IMAGES_SETTING.save(pool, self).await
+    }
+
+    /// Validate the eager image count
+    pub fn validate(&self) -> Result<()> {
+        if self.policy.eager_images > MAX_EAGER_IMAGES {
+            return Err(Error::invalid_input(
+                "eager_images",
+                format!("At most {} images can load eagerly", MAX_EAGER_IMAGES),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// A generated version of a media library image
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct MediaDerivative {
+    pub media_id: Uuid,
+    pub size_name: String,
+    pub storage_path: String,
+    pub width: i32,
+    pub height: i32,
+}
+
+#[derive(Debug, FromRow)]
+struct MediaImageRow {
+    id: Uuid,
+    storage_path: String,
+    mime_type: String,
+    width: Option<i32>,
+    height: Option<i32>,
+}
+
+/// Whether versions can be made of an image; animated GIFs would lose
+/// their animation
+fn is_resizable(mime_type: &str) -> bool {
+    matches!(mime_type, "image/jpeg" | "image/png" | "image/webp")
+}
+
+pub struct ResponsiveImagesService {
+    pool: PgPool,
+    storage: Arc<Storage>,
+    generator: Arc<ResponsiveImageGenerator>,
+    resizes: Semaphore,
+    config: RwLock<Option<Arc<ResponsiveImagesConfig>>>,
+}
+
+impl ResponsiveImagesService {
+    pub fn new(
+        pool: PgPool,
+        storage: Arc<Storage>,
+        generator: Arc<ResponsiveImageGenerator>,
+    ) -> Self {
+        Self {
+            pool,
+            storage,
+            generator,
+            resizes: Semaphore::new(MAX_CONCURRENT_RESIZES),
+            config: RwLock::new(None),
+        }
+    }
+
+    /// Current configuration, loaded from settings on first use
+    pub async fn config(&self) -> Arc<ResponsiveImagesConfig> {
+        if let Some(config) = self.config.read().await.clone() {
+            return config;
+        }
+
+        match ResponsiveImagesConfig::load(&self.pool).await {
+            Ok(config) => {
+                let config = Arc::new(config);
+                *self.config.write().await = Some(config.clone());
+                config
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "Failed to load responsive image settings, using defaults: {}",
+                    e
+                );
+                Arc::new(ResponsiveImagesConfig::default())
+            }
+        }
+    }
+
+    /// Validate and save a new configuration
+    pub async fn update_config(
+        &self,
+        config: ResponsiveImagesConfig,
+    ) -> Result<ResponsiveImagesConfig> {
+        config.validate()?;
+        config.save(&self.pool).await?;
+        *self.config.write().await = Some(Arc::new(config.clone()));
+        Ok(config)
+    }
+
+    /// Drop the cached configuration; the next use reloads it
+    pub async fn invalidate(&self) {
+        *self.config.write().await = None;
+    }
+
+    /// Image sizes versions are made for
+    pub fn sizes(&self) -> Vec<ImageSize> {
+        self.generator.sizes()
+    }
+
+    /// Post-process a rendered page, see the module documentation
+    pub async fn apply(&self, html: String) -> String {
+        let config = self.config().await;
+        if !config.enabled {
+            return html;
+        }
+
+        let known = match self.known_images(&image_sources(&html), &config).await {
+            Ok(known) => known,
+            Err(e) => {
+                tracing::warn!("Failed to look up the images of a page: {}", e);
+                HashMap::new()
+            }
+        };
+        optimize_images(&html, &config.policy, |src| known.get(src))
+    }
+
+    /// Dimensions and versions of the media library images among `sources`
+    async fn known_images(
+        &self,
+        sources: &[String],
+        config: &ResponsiveImagesConfig,
+    ) -> Result<HashMap<String, KnownImage>> {
+        let mut paths: HashMap<String, Vec<&String>> = HashMap::new();
+        for src in sources.iter().take(MAX_IMAGES_PER_PAGE) {
+            if let Some(path) = self.storage_path_of(src) {
+                paths.entry(path).or_default().push(src);
+            }
+        }
+        if paths.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let keys: Vec<String> = paths.keys().cloned().collect();
+        let mut rows: Vec<MediaImageRow> = sqlx::query_as(&format!(
+            r#"
+            SELECT id, storage_path, mime_type, width, height
+            FROM media
+            WHERE storage_path = ANY($1) AND deleted_at IS NULL AND {}
+            "#,
+            site_filter("site_id")
+        ))
+        .bind(&keys)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to look up media images", e))?;
+
+        // Measure the images the library has no dimensions for, a few per page
+        let unmeasured = rows
+            .iter_mut()
+            .filter(|row| {
+                row.mime_type.starts_with("image/")
+                    && row.mime_type != "image/svg+xml"
+                    && (row.width.is_none() || row.height.is_none())
+            })
+            .take(MAX_PROBES_PER_PAGE);
+        for row in unmeasured {
+            if let Some((width, height)) = self.measure(row.id, &row.storage_path).await {
+                row.width = Some(width);
+                row.height = Some(height);
+            }
+        }
+
+        let ids: Vec<Uuid> = rows.iter().map(|row| row.id).collect();
+        let derivatives: Vec<MediaDerivative> = sqlx::query_as(
+            r#"
+            SELECT media_id, size_name, storage_path, width, height
+            FROM media_derivatives
+            WHERE media_id = ANY($1)
+            "#,
+        )
+        .bind(&ids)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to look up image versions", e))?;
+        let mut stored: HashMap<(Uuid, &str), &MediaDerivative> = HashMap::new();
+        for derivative in &derivatives {
+            stored.insert((derivative.media_id, &derivative.size_name), derivative);
+        }
+
+        let mut known = HashMap::new();
+        for row in &rows {
+            let (Some(width), Some(height)) = (row.width, row.height) else {
+                continue;
+            };
+            let (Ok(width), Ok(height)) = (u32::try_from(width), u32::try_from(height)) else {
+                continue;
+            };
+            let Some(srcs) = paths.get(&row.storage_path) else {
+                continue;
+            };
+
+            let srcset = if config.policy.srcset && is_resizable(&row.mime_type) {
+                self.generator.srcset_candidates(width, height, |size| {
+                    match stored.get(&(row.id, size.name.as_str())) {
+                        Some(derivative) => Some(self.url(&derivative.storage_path)),
+                        None => config
+                            .transform_missing
+                            .then(|| format!("{}/{}/{}", TRANSFORM_PATH, row.id, size.name)),
+                    }
+                })
+            } else {
+                Vec::new()
+            };
+            let image = KnownImage {
+                width,
+                height,
+                srcset,
+            };
+            for src in srcs {
+                known.insert((*src).clone(), image.clone());
+            }
+        }
+
+        Ok(known)
+    }
+
+    /// Read an image's dimensions from its file and record them
+    async fn measure(&self, id: Uuid, storage_path: &str) -> Option<(i32, i32)> {
+        let data = match self.storage.get(storage_path).await {
+            Ok(data) => data,
+            Err(e) => {
+                tracing::debug!(media_id = %id, "Failed to read image to measure it: {}", e);
+                return None;
+            }
+        };
+        let (width, height) = probe_dimensions(&data)?;
+        let (width, height) = (i32::try_from(width).ok()?, i32::try_from(height).ok()?);
+
+        let updated = sqlx::query(
+            "UPDATE media SET width = $2, height = $3 WHERE id = $1 AND (width IS NULL OR height IS NULL)",
+        )
+        .bind(id)
+        .bind(width)
+        .bind(height)
+        .execute(&self.pool)
+        .await;
+        if let Err(e) = updated {
+            tracing::warn!(media_id = %id, "Failed to record image dimensions: {}", e);
+        }
+
+        Some((width, height))
+    }
+
+    /// Versions generated of an image
+    pub async fn derivatives(&self, media_id: Uuid) -> Result<Vec<MediaDerivative>> {
+        sqlx::query_as(&format!(
+            r#"
+            SELECT media_id, size_name, storage_path, width, height
+            FROM media_derivatives
+            WHERE media_id = $1 AND media_id IN (SELECT id FROM media WHERE {})
+            ORDER BY width
+            "#,
+            site_filter("site_id")
+        ))
+        .bind(media_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to list image versions", e))
+    }
+
+    /// URL of an image at a registered size, generating and storing the
+    /// version on first request. Images too small for the size are served
+    /// as they are
+    pub async fn transform(&self, media_id: Uuid, size_name: &str) -> Result<String> {
+        let size = self
+            .generator
+            .size(size_name)
+            .ok_or_else(|| Error::not_found("Image size", size_name))?;
+        let row: MediaImageRow = sqlx::query_as(&format!(
+            r#"
+            SELECT id, storage_path, mime_type, width, height
+            FROM media
+            WHERE id = $1 AND deleted_at IS NULL AND storage_path IS NOT NULL AND {}
+            "#,
+            site_filter("site_id")
+        ))
+        .bind(media_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load media", e))?
+        .ok_or_else(|| Error::not_found("Media", media_id.to_string()))?;
+        if !is_resizable(&row.mime_type) {
+            return Err(Error::invalid_input(
+                "media",
+                format!("Images of type {} are not resized", row.mime_type),
+            ));
+        }
+
+        if let Some(path) = self.stored_derivative(media_id, size_name).await? {
+            return Ok(self.url(&path));
+        }
+
+        // Only a few images are resized at once; the rest wait their turn
+        // and often find their version made by an earlier request
+        let _permit = self
+            .resizes
+            .acquire()
+            .await
+            .map_err(|_| Error::internal("Image resizing is shut down"))?;
+        if let Some(path) = self.stored_derivative(media_id, size_name).await? {
+            return Ok(self.url(&path));
+        }
+
+        let data = self.storage.get(&row.storage_path).await?;
+        if data.len() > MAX_SOURCE_SIZE {
+            return Ok(self.url(&row.storage_path));
+        }
+        let generator = self.generator.clone();
+        let derived = tokio::task::spawn_blocking(move || generator.derive(&data, &size))
+            .await
+            .map_err(|e| Error::internal(format!("Image resizing failed: {}", e)))?
+            .map_err(|e| Error::internal(format!("Failed to resize image: {}", e)))?;
+        let Some(derived) = derived else {
+            return Ok(self.url(&row.storage_path));
+        };
+
+        let stem = std::path::Path::new(&row.storage_path)
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("image");
+        let file_size = derived.data.len() as i64;
+        let stored = self
+            .storage
+            .upload_to(
+                Bytes::from(derived.data),
+                &format!("{}-{}.webp", stem, size_name),
+                "image/webp",
+                DERIVATIVES_DIRECTORY,
+            )
+            .await?;
+
+        let inserted = sqlx::query(
+            r#"
+            INSERT INTO media_derivatives (media_id, size_name, storage_path, width, height, file_size)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (media_id, size_name) DO NOTHING
+            "#,
+        )
+        .bind(media_id)
+        .bind(size_name)
+        .bind(&stored.path)
+        .bind(derived.width as i32)
+        .bind(derived.height as i32)
+        .bind(file_size)
+        .execute(&self.pool)
+        .await;
+        match inserted {
+            Ok(result) if result.rows_affected() == 1 => Ok(self.url(&stored.path)),
+            Ok(_) => {
+                // Another server made the version first
+                let _ = self.storage.delete(&stored.path).await;
+                let path = self.stored_derivative(media_id, size_name).await?;
+                Ok(self.url(path.as_deref().unwrap_or(&row.storage_path)))
+            }
+            Err(e) => {
+                let _ = self.storage.delete(&stored.path).await;
+                Err(Error::database_with_source(
+                    "Failed to record image version",
+                    e,
+                ))
+            }
+        }
+    }
+
+    /// Delete the versions generated of an image, e.g. after it was edited
+    pub async fn purge(&self, media_id: Uuid) -> Result<u64> {
+        let paths: Vec<(String,)> = sqlx::query_as(&format!(
+            "DELETE FROM media_derivatives WHERE media_id = $1 \
+             AND media_id IN (SELECT id FROM media WHERE {}) RETURNING storage_path",
+            site_filter("site_id")
+        ))
+        .bind(media_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to delete image versions", e))?;
+
+        for (path,) in &paths {
+            if let Err(e) = self.storage.delete(path).await {
+                tracing::warn!(media_id = %media_id, "Failed to delete image version {}: {}", path, e);
+            }
+        }
+        Ok(paths.len() as u64)
+    }
+
+    async fn stored_derivative(&self, media_id: Uuid, size_name: &str) -> Result<Option<String>> {
+        let row: Option<(String,)> = sqlx::query_as(
+            "SELECT storage_path FROM media_derivatives WHERE media_id = $1 AND size_name = $2",
+        )
+        .bind(media_id)
+        .bind(size_name)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to look up image version", e))?;
+        Ok(row.map(|(path,)| path))
+    }
+
+    /// Public URL of a file in storage
+    fn url(&self, path: &str) -> String {
+        self.storage
+            .url(path)
+            .unwrap_or_else(|| format!("/uploads/{}", path))
+    }
+
+    /// Storage path of an image URL served from storage
+    fn storage_path_of(&self, src: &str) -> Option<String> {
+        storage_path_of(src, self.storage.url("").as_deref())
+    }
+}
+
+/// Storage path of an image URL, from the storage's public URL of the
+/// empty path or the `/uploads/` prefix local storage is served under
+fn storage_path_of(src: &str, storage_base: Option<&str>) -> Option<String> {
+    let src = src.split(['?', '#']).next().unwrap_or_default();
+    let path = storage_base
+        .filter(|base| !base.is_empty())
+        .and_then(|base| src.strip_prefix(base))
+        .or_else(|| src.split_once("/uploads/").map(|(_, path)| path))?;
+    let path = path.trim_start_matches('/');
+
+    (!path.is_empty() && !path.split('/').any(|part| part == "..")).then(|| path.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn storage_paths_from_image_urls() {
+        assert_eq!(
+            storage_path_of("/uploads/2026/10/18/a.jpg?v=2", Some("/uploads/")),
+            Some("2026/10/18/a.jpg".to_string())
+        );
+        assert_eq!(
+            storage_path_of(
+                "https://cdn.example/media/x/a.png",
+                Some("https://cdn.example/media/")
+            ),
+            Some("x/a.png".to_string())
+        );
+        assert_eq!(
+            storage_path_of("https://example.com/uploads/a.png", None),
+            Some("a.png".to_string())
+        );
+        assert_eq!(storage_path_of("/uploads/../secret", None), None);
+        assert_eq!(storage_path_of("/images/logo.png", Some("/uploads/")), None);
+    }
+
+    #[test]
+    fn config_defaults_and_validation() {
+        let config: ResponsiveImagesConfig =
+            serde_json::from_value(serde_json::json!({ "eager_images": 3 })).unwrap();
+        assert!(config.enabled && config.policy.lazy_images && config.transform_missing);
+        assert_eq!(config.policy.eager_images, 3);
+        assert!(config.validate().is_ok());
+
+        let mut config = config;
+        config.policy.eager_images = MAX_EAGER_IMAGES + 1;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn only_still_images_are_resized() {
+        assert!(is_resizable("image/jpeg"));
+        assert!(!is_resizable("image/gif"));
+        assert!(!is_resizable("image/svg+xml"));
+    }
+}
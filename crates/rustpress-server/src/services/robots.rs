@@ -0,0 +1,440 @@
+//! Robots and Crawl Control
+//!
+//! Builds `robots.txt` from managed settings instead of a hand-edited file:
+//! site sections to keep out of crawls, a sitemap reference, opt-outs for
+//! AI training crawlers and per-environment overrides, so a staging copy
+//! never gets indexed. Also computes the robots meta directives for rendered
+//! pages from the environment and per-post `seo_noindex`/`seo_nofollow` meta.
+
+use chrono::{Datelike, Utc};
+use rustpress_core::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::PgPool;
+use std::collections::HashMap;
+
+use super::json_setting::JsonSetting;
+use super::regions::inject_head_tags;
+
+/// Settings key holding the robots configuration
+pub const ROBOTS_SETTINGS_KEY: &str = "robots_config";
+
+/// Stored robots settings
+const ROBOTS_SETTING: JsonSetting<RobotsConfig> =
+    JsonSetting::new(ROBOTS_SETTINGS_KEY, "seo", "robots settings");
+
+/// Known AI training and answer-engine crawlers
+pub const AI_CRAWLERS: &[&str] = &[
+    "GPTBot",
+    "ChatGPT-User",
+    "OAI-SearchBot",
+    "ClaudeBot",
+    "anthropic-ai",
+    "Google-Extended",
+    "Applebot-Extended",
+    "CCBot",
+    "PerplexityBot",
+    "Bytespider",
+    "meta-externalagent",
+    "Amazonbot",
+    "cohere-ai",
+];
+
+/// Site sections that can be kept out of crawls
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SiteSection {
+    Admin,
+    Api,
+    Search,
+    Feeds,
+    Tags,
+    Authors,
+    DateArchives,
+}
+
+/// First year covered by [`SiteSection::DateArchives`]
+const FIRST_ARCHIVE_YEAR: i32 = 1995;
+
+impl SiteSection {
+    /// Path patterns covering the section
+    pub fn paths(&self) -> Vec<String> {
+        let paths: &[&str] = match self {
+            Self::Admin => &["/admin/"],
+            Self::Api => &["/api/"],
+            Self::Search => &["/search"],
+            Self::Feeds => &["/feed", "/*/feed$", "/*/feed/"],
+            Self::Tags => &["/tag/"],
+            Self::Authors => &["/author/"],
+            // robots.txt has no digit wildcard, so list each year segment
+            // rather than a prefix that would also match `/1password/`
+            Self::DateArchives => {
+                return (FIRST_ARCHIVE_YEAR..=Utc::now().year() + 1)
+                    .map(|year| format!("/{}/", year))
+                    .collect();
+            }
+        };
+        paths.iter().map(|path| path.to_string()).collect()
+    }
+}
+
+/// Rules for one crawler
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AgentRules {
+    pub user_agent: String,
+    #[serde(default)]
+    pub allow: Vec<String>,
+    #[serde(default)]
+    pub disallow: Vec<String>,
+    #[serde(default)]
+    pub crawl_delay: Option<u32>,
+}
+
+/// Overrides applied in one deployment environment
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EnvironmentOverride {
+    /// Disallow everything and mark every page `noindex, nofollow`
+    #[serde(default)]
+    pub block_indexing: bool,
+    /// Extra paths disallowed for all crawlers
+    #[serde(default)]
+    pub disallow: Vec<String>,
+}
+
+/// Managed robots.txt configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RobotsConfig {
+    /// Sections disallowed for all crawlers
+    pub disallow_sections: Vec<SiteSection>,
+    /// Extra paths disallowed for all crawlers
+    pub disallow: Vec<String>,
+    /// Paths explicitly allowed for all crawlers
+    pub allow: Vec<String>,
+    /// Reference the sitemap
+    pub include_sitemap: bool,
+    /// Sitemap URL; defaults to `{site_url}/sitemap.xml`
+    pub sitemap_url: Option<String>,
+    /// Block every crawler in [`AI_CRAWLERS`]
+    pub block_ai_crawlers: bool,
+    /// Additional user agents blocked from the whole site
+    pub blocked_agents: Vec<String>,
+    /// Rules for specific crawlers
+    pub agents: Vec<AgentRules>,
+    /// Overrides keyed by environment name (`RUSTPRESS_ENV`)
+    pub environments: HashMap<String, EnvironmentOverride>,
+    /// Raw lines appended verbatim
+    pub custom_rules: Option<String>,
+}
+
+impl Default for RobotsConfig {
+    fn default() -> Self {
+        let blocked = EnvironmentOverride {
+            block_indexing: true,
+            disallow: Vec::new(),
+        };
+
+        Self {
+            disallow_sections: vec![SiteSection::Admin, SiteSection::Api, SiteSection::Search],
+            disallow: Vec::new(),
+            allow: Vec::new(),
+            include_sitemap: true,
+            sitemap_url: None,
+            block_ai_crawlers: false,
+            blocked_agents: Vec::new(),
+            agents: Vec::new(),
+            environments: HashMap::from([
+                ("staging".to_string(), blocked.clone()),
+                ("development".to_string(), blocked),
+            ]),
+            custom_rules: None,
+        }
+    }
+}
+
+impl RobotsConfig {
+    /// Load the configuration from settings, falling back to defaults
+    pub async fn load(pool: &PgPool) -> Result<Self> {
+        ROBOTS_SETTING.load_or_default(pool).await
+    }
+
+    /// Persist the configuration to settings
+    pub async fn save(&self, pool: &PgPool) -> Result<()> {
+        ROBOTS_SETTING.save(pool, self).await
+    }
+
+    /// Validate paths and user agents
+    pub fn validate(&self) -> Result<()> {
+        let paths = self
+            .disallow
+            .iter()
+            .chain(&self.allow)
+            .chain(
+                self.agents
+                    .iter()
+                    .flat_map(|a| a.allow.iter().chain(&a.disallow)),
+            )
+            .chain(self.environments.values().flat_map(|e| &e.disallow));
+        for path in paths {
+            if !path.starts_with('/') && !path.starts_with('*') {
+                return Err(Error::invalid_input(
+                    "robots",
+                    format!("Path '{}' must start with '/'", path),
+                ));
+            }
+            if path.contains(['\n', '\r']) {
+                return Err(Error::invalid_input(
+                    "robots",
+                    format!("Path '{}' must be a single line", path.escape_debug()),
+                ));
+            }
+        }
+        if let Some(url) = &self.sitemap_url {
+            if url.contains(['\n', '\r']) {
+                return Err(Error::invalid_input(
+                    "robots",
+                    "Sitemap URL must be a single line",
+                ));
+            }
+        }
+
+        let agents = self
+            .blocked_agents
+            .iter()
+            .chain(self.agents.iter().map(|a| &a.user_agent));
+        for agent in agents {
+            if agent.trim().is_empty() || agent.contains(['\n', '\r', ':']) {
+                return Err(Error::invalid_input(
+                    "robots",
+                    format!("Invalid user agent '{}'", agent),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether indexing is blocked in an environment
+    pub fn blocks_indexing(&self, environment: &str) -> bool {
+        self.environments
+            .get(environment)
+            .is_some_and(|o| o.block_indexing)
+    }
+
+    /// Render robots.txt for an environment
+    pub fn render(&self, environment: &str, site_url: &str) -> String {
+        let site_url = site_url.trim_end_matches('/');
+        let mut out = String::new();
+
+        if self.blocks_indexing(environment) {
+            out.push_str(&format!(
+                "# Indexing is disabled in the {} environment\nUser-agent: *\nDisallow: /\n",
+                environment
+            ));
+            return out;
+        }
+
+        let section_paths: Vec<String> = self
+            .disallow_sections
+            .iter()
+            .flat_map(SiteSection::paths)
+            .collect();
+        let mut disallow: Vec<&str> = section_paths
+            .iter()
+            .chain(&self.disallow)
+            .map(String::as_str)
+            .collect();
+        if let Some(env) = self.environments.get(environment) {
+            disallow.extend(env.disallow.iter().map(String::as_str));
+        }
+        dedup(&mut disallow);
+
+        out.push_str("User-agent: *\n");
+        for path in &self.allow {
+            out.push_str(&format!("Allow: {}\n", path));
+        }
+        for path in &disallow {
+            out.push_str(&format!("Disallow: {}\n", path));
+        }
+        if self.allow.is_empty() && disallow.is_empty() {
+            out.push_str("Allow: /\n");
+        }
+
+        for rules in &self.agents {
+            out.push_str(&format!("\nUser-agent: {}\n", rules.user_agent));
+            for path in &rules.allow {
+                out.push_str(&format!("Allow: {}\n", path));
+            }
+            for path in &rules.disallow {
+                out.push_str(&format!("Disallow: {}\n", path));
+            }
+            if let Some(delay) = rules.crawl_delay {
+                out.push_str(&format!("Crawl-delay: {}\n", delay));
+            }
+        }
+
+        let mut blocked: Vec<&str> = Vec::new();
+        if self.block_ai_crawlers {
+            blocked.extend(AI_CRAWLERS);
+        }
+        blocked.extend(self.blocked_agents.iter().map(String::as_str));
+        dedup(&mut blocked);
+        if !blocked.is_empty() {
+            out.push_str("\n# Opted out of AI crawlers and blocked agents\n");
+            for agent in blocked {
+                out.push_str(&format!("User-agent: {}\n", agent));
+            }
+            out.push_str("Disallow: /\n");
+        }
+
+        if let Some(ref custom) = self.custom_rules {
+            if !custom.trim().is_empty() {
+                out.push('\n');
+                out.push_str(custom.trim_end());
+                out.push('\n');
+            }
+        }
+
+        if self.include_sitemap {
+            let sitemap = self
+                .sitemap_url
+                .clone()
+                .unwrap_or_else(|| format!("{}/sitemap.xml", site_url));
+            out.push_str(&format!("\nSitemap: {}\n", sitemap));
+        }
+
+        out
+    }
+
+    /// Robots meta directives for a rendered page, if any.
+    ///
+    /// Environments that block indexing mark every page `noindex, nofollow`;
+    /// otherwise posts opt out with the `seo_noindex`/`seo_nofollow` meta.
+    pub fn meta_directives(
+        &self,
+        environment: &str,
+        post_meta: Option<&HashMap<String, Value>>,
+    ) -> Option<String> {
+        if self.blocks_indexing(environment) {
+            return Some("noindex, nofollow".to_string());
+        }
+
+        let meta = post_meta?;
+        let directives: Vec<&str> = [("seo_noindex", "noindex"), ("seo_nofollow", "nofollow")]
+            .iter()
+            .filter(|(key, _)| meta.get(*key).is_some_and(is_truthy))
+            .map(|(_, directive)| *directive)
+            .collect();
+
+        if directives.is_empty() {
+            None
+        } else {
+            Some(directives.join(", "))
+        }
+    }
+}
+
+/// Insert a robots meta tag before `</head>`
+pub fn inject_robots_meta(html: &str, directives: &str) -> String {
+    let tag = format!(
+        "<meta name=\"robots\" content=\"{}\">",
+        tera::escape_html(directives)
+    );
+    inject_head_tags(html, &tag)
+}
+
+fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_i64() == Some(1),
+        Value::String(s) => matches!(s.trim(), "1" | "true" | "yes" | "on"),
+        _ => false,
+    }
+}
+
+fn dedup(items: &mut Vec<&str>) {
+    let mut seen = std::collections::HashSet::new();
+    items.retain(|item| seen.insert(*item));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_robots() {
+        let config = RobotsConfig {
+            disallow_sections: vec![SiteSection::Admin, SiteSection::Tags],
+            disallow: vec!["/private/".to_string(), "/admin/".to_string()],
+            block_ai_crawlers: true,
+            blocked_agents: vec!["BadBot".to_string()],
+            agents: vec![AgentRules {
+                user_agent: "Bingbot".to_string(),
+                crawl_delay: Some(5),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        let robots = config.render("production", "https://example.com/");
+        assert!(robots.starts_with(
+            "User-agent: *\nDisallow: /admin/\nDisallow: /tag/\nDisallow: /private/\n"
+        ));
+        assert!(robots.contains("User-agent: Bingbot\nCrawl-delay: 5\n"));
+        assert!(robots.contains("User-agent: GPTBot\n"));
+        assert!(robots.contains("User-agent: BadBot\nDisallow: /\n"));
+        assert!(robots.ends_with("Sitemap: https://example.com/sitemap.xml\n"));
+
+        let staging = config.render("staging", "https://staging.example.com");
+        assert!(staging.contains("User-agent: *\nDisallow: /\n"));
+        assert!(!staging.contains("Sitemap"));
+    }
+
+    #[test]
+    fn test_meta_directives() {
+        let config = RobotsConfig::default();
+        let meta = HashMap::from([
+            ("seo_noindex".to_string(), Value::String("true".to_string())),
+            ("seo_nofollow".to_string(), Value::Bool(false)),
+        ]);
+
+        assert_eq!(
+            config.meta_directives("production", Some(&meta)),
+            Some("noindex".to_string())
+        );
+        assert_eq!(config.meta_directives("production", None), None);
+        assert_eq!(
+            config.meta_directives("staging", None),
+            Some("noindex, nofollow".to_string())
+        );
+
+        let html = inject_robots_meta("<html><HEAD><title>x</title></HEAD></html>", "noindex");
+        assert_eq!(
+            html,
+            "<html><HEAD><title>x</title><meta name=\"robots\" content=\"noindex\">\n</HEAD></html>"
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut config = RobotsConfig::default();
+        assert!(config.validate().is_ok());
+        config.disallow.push("private".to_string());
+        assert!(config.validate().is_err());
+
+        config.disallow = vec!["/private/\nUser-agent: *\nAllow: /".to_string()];
+        assert!(config.validate().is_err());
+        config.disallow.clear();
+        config.sitemap_url = Some("https://example.com/sitemap.xml\rDisallow: /".to_string());
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_date_archive_paths_match_years_only() {
+        let paths = SiteSection::DateArchives.paths();
+        assert!(paths.contains(&"/2024/".to_string()));
+        assert!(paths.iter().all(|path| {
+            let year = &path[1..path.len() - 1];
+            year.len() == 4 && year.bytes().all(|b| b.is_ascii_digit())
+        }));
+    }
+}
@@ -0,0 +1,424 @@
+//! SAML Single Sign-On
+//!
+//! Serves the [`SamlClient`] of `rustpress-auth` as this site's service
+//! provider. Identity providers are configured by administrators and kept
+//! in settings; the service provider's entity id and assertion consumer
+//! service are derived from the public site URL, so the IdP is pointed at
+//! the metadata URL once and picks up both.
+//!
+//! Pending requests and consumed assertion ids live in the database, so the
+//! IdP may post its response to any instance and an assertion is accepted
+//! only once across all of them. A verified user is matched to an account by
+//! e-mail address; unknown addresses get a new account with the role the
+//! provider's attribute mapping resolves. Existing accounts keep their role.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rustpress_auth::saml::{
+    SamlClient, SamlConfig, SamlIdentityProvider, SamlRequestState, SamlRequestStore,
+    SamlServiceProvider, SamlUserInfo,
+};
+use rustpress_auth::PasswordHasher;
+use rustpress_core::error::{Error, Result};
+use rustpress_database::repository::users::{UserRepository, UserRow};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::HashSet;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use super::json_setting::JsonSetting;
+
+/// Settings key holding the identity providers
+pub const SAML_SSO_SETTINGS_KEY: &str = "saml_sso_config";
+
+/// Stored SAML settings
+const SAML_SSO_SETTING: JsonSetting<SamlSsoConfig> =
+    JsonSetting::new(SAML_SSO_SETTINGS_KEY, "security", "SAML settings");
+
+/// Path of the service provider metadata, also used as its entity id
+pub const SAML_METADATA_PATH: &str = "/api/v1/auth/saml/metadata";
+
+/// Path of the assertion consumer service
+pub const SAML_ACS_PATH: &str = "/api/v1/auth/saml/acs";
+
+/// Client for this site's service provider
+pub type SamlSsoClient = SamlClient<SamlRequestDbStore>;
+
+/// Identity providers users may sign in with
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SamlSsoConfig {
+    pub providers: Vec<SamlIdentityProvider>,
+}
+
+impl SamlSsoConfig {
+    /// Load the configuration from settings
+    pub async fn load(pool: &PgPool) -> Result<Self> {
+        SAML_SSO_SETTING.load_or_default(pool).await
+    }
+
+    /// Persist the configuration
+    pub async fn save(&self, pool: &PgPool) -> Result<()> {
+        SAML_SSO_SETTING.save(pool, self).await
+    }
+
+    /// Check provider names, which appear in login URLs, and certificates
+    pub fn validate(&self) -> Result<()> {
+        let mut names = HashSet::new();
+        for provider in &self.providers {
+            let valid_name = !provider.name.is_empty()
+                && provider
+                    .name
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+            if !valid_name {
+                return Err(Error::invalid_input(
+                    "providers",
+                    format!(
+                        "Provider name '{}' may only use lowercase letters, digits, '-' and '_'",
+                        provider.name
+                    ),
+                ));
+            }
+            if !names.insert(provider.name.as_str()) {
+                return Err(Error::invalid_input(
+                    "providers",
+                    format!("Provider '{}' is configured twice", provider.name),
+                ));
+            }
+        }
+
+        // Registering parses every certificate
+        let mut client = SamlClient::new(
+            SamlServiceProvider::new(String::new(), String::new()),
+            rustpress_auth::saml::InMemorySamlRequestStore::new(),
+            SamlConfig::default(),
+        );
+        for provider in &self.providers {
+            client.register_provider(provider.clone())?;
+        }
+        Ok(())
+    }
+}
+
+/// Pending requests and seen assertions, in the database
+pub struct SamlRequestDbStore {
+    pool: PgPool,
+}
+
+impl SamlRequestDbStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl SamlRequestStore for SamlRequestDbStore {
+    async fn store_request(&self, request: &SamlRequestState) -> Result<()> {
+        // Abandoned logins are only kept until the next one starts
+        self.cleanup_expired().await?;
+        sqlx::query(
+            r#"
+            INSERT INTO saml_requests (id, provider, redirect_after, expires_at, created_at)
+            VALUES ($1, $2, $3, $4, $5)
+            "#,
+        )
+        .bind(&request.id)
+        .bind(&request.provider)
+        .bind(&request.redirect_after)
+        .bind(request.expires_at)
+        .bind(request.created_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to store SAML request", e))?;
+        Ok(())
+    }
+
+    async fn get_request(&self, id: &str) -> Result<Option<SamlRequestState>> {
+        let row: Option<(String, String, Option<String>, DateTime<Utc>, DateTime<Utc>)> =
+            sqlx::query_as(
+                "SELECT id, provider, redirect_after, expires_at, created_at \
+                 FROM saml_requests WHERE id = $1",
+            )
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to load SAML request", e))?;
+
+        Ok(row.map(
+            |(id, provider, redirect_after, expires_at, created_at)| SamlRequestState {
+                id,
+                provider,
+                redirect_after,
+                expires_at,
+                created_at,
+            },
+        ))
+    }
+
+    async fn delete_request(&self, id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM saml_requests WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to delete SAML request", e))?;
+        Ok(())
+    }
+
+    async fn record_assertion(&self, id: &str, expires_at: DateTime<Utc>) -> Result<bool> {
+        // An id is free again once its previous use has expired
+        let recorded: Option<(String,)> = sqlx::query_as(
+            r#"
+            INSERT INTO saml_assertions (id, expires_at)
+            VALUES ($1, $2)
+            ON CONFLICT (id) DO UPDATE SET expires_at = EXCLUDED.expires_at
+            WHERE saml_assertions.expires_at <= NOW()
+            RETURNING id
+            "#,
+        )
+        .bind(id)
+        .bind(expires_at)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to record SAML assertion", e))?;
+        Ok(recorded.is_some())
+    }
+
+    async fn cleanup_expired(&self) -> Result<u64> {
+        let mut removed = 0;
+        for table in ["saml_requests", "saml_assertions"] {
+            removed += sqlx::query(&format!("DELETE FROM {} WHERE expires_at < NOW()", table))
+                .execute(&self.pool)
+                .await
+                .map_err(|e| Error::database_with_source("Failed to clean up SAML state", e))?
+                .rows_affected();
+        }
+        Ok(removed)
+    }
+}
+
+/// SAML single sign-on for the site
+pub struct SamlSsoService {
+    pool: PgPool,
+    config: RwLock<Option<Arc<SamlSsoConfig>>>,
+}
+
+impl SamlSsoService {
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            config: RwLock::new(None),
+        }
+    }
+
+    /// SAML configuration, loaded from settings on first use
+    pub async fn config(&self) -> Arc<SamlSsoConfig> {
+        if let Some(config) = self.config.read().await.clone() {
+            return config;
+        }
+
+        match SamlSsoConfig::load(&self.pool).await {
+            Ok(config) => {
+                let config = Arc::new(config);
+                *self.config.write().await = Some(config.clone());
+                config
+            }
+            Err(e) => {
+                tracing::warn!("Failed to load SAML settings, disabling SAML login: {}", e);
+                Arc::new(SamlSsoConfig::default())
+            }
+        }
+    }
+
+    /// Replace the cached configuration after it was saved
+    pub async fn set_config(&self, config: SamlSsoConfig) {
+        *self.config.write().await = Some(Arc::new(config));
+    }
+
+    /// Drop the cached configuration; the next use reloads it
+    pub async fn invalidate(&self) {
+        *self.config.write().await = None;
+    }
+
+    /// Client for the site at `site_url`, with the configured providers
+    pub async fn client(&self, site_url: &str) -> SamlSsoClient {
+        let mut client = SamlClient::new(
+            service_provider(site_url),
+            SamlRequestDbStore::new(self.pool.clone()),
+            SamlConfig::default(),
+        );
+        for provider in &self.config().await.providers {
+            if let Err(e) = client.register_provider(provider.clone()) {
+                tracing::warn!(provider = %provider.name, "Skipping SAML provider: {}", e);
+            }
+        }
+        client
+    }
+
+    /// The account a verified SAML user signs in as, created on first login
+    pub async fn sign_in(&self, info: &SamlUserInfo) -> Result<Uuid> {
+        let email = info
+            .email
+            .as_deref()
+            .map(|email| email.trim().to_lowercase())
+            .filter(|email| email.contains('@'))
+            .ok_or_else(|| {
+                Error::unauthorized("The identity provider did not assert an e-mail address")
+            })?;
+
+        let users = UserRepository::new(self.pool.clone());
+        if let Some(user) = users.find_by_email(&email).await? {
+            users.update_last_login(user.id).await?;
+            return Ok(user.id);
+        }
+
+        let role = info
+            .role
+            .clone()
+            .ok_or_else(|| Error::forbidden("No role is mapped for this account"))?;
+        let username = self.free_username(&users, &email).await?;
+        // Accounts created here sign in through the IdP only
+        let password_hash = PasswordHasher::new().hash(&Uuid::new_v4().to_string())?;
+        let display_name = info.name.clone().or_else(|| {
+            let parts: Vec<&str> = [info.first_name.as_deref(), info.last_name.as_deref()]
+                .into_iter()
+                .flatten()
+                .collect();
+            (!parts.is_empty()).then(|| parts.join(" "))
+        });
+
+        let now = Utc::now();
+        let user = users
+            .create(&UserRow {
+                id: Uuid::now_v7(),
+                email,
+                username,
+                password_hash,
+                display_name,
+                status: "active".to_string(),
+                role,
+                avatar_url: None,
+                locale: None,
+                timezone: None,
+                email_verified_at: Some(now),
+                last_login_at: Some(now),
+                created_at: now,
+                updated_at: now,
+                deleted_at: None,
+            })
+            .await?;
+
+        tracing::info!(
+            user_id = %user.id,
+            provider = %info.provider,
+            role = %user.role,
+            "Account created at first SAML login"
+        );
+        Ok(user.id)
+    }
+
+    /// A username from the e-mail's local part that nobody uses yet
+    async fn free_username(&self, users: &UserRepository, email: &str) -> Result<String> {
+        let base = username_from_email(email);
+        if users.find_by_username(&base).await?.is_none() {
+            return Ok(base);
+        }
+        for _ in 0..5 {
+            let candidate = format!("{}-{}", base, &Uuid::new_v4().simple().to_string()[..6]);
+            if users.find_by_username(&candidate).await?.is_none() {
+                return Ok(candidate);
+            }
+        }
+        Err(Error::internal("Could not find a free username"))
+    }
+}
+
+/// This site as a service provider
+pub fn service_provider(site_url: &str) -> SamlServiceProvider {
+    let site_url = site_url.trim_end_matches('/');
+    SamlServiceProvider::new(
+        format!("{}{}", site_url, SAML_METADATA_PATH),
+        format!("{}{}", site_url, SAML_ACS_PATH),
+    )
+}
+
+/// Whether `target` is a path on this site, safe to redirect to after login
+pub fn is_local_redirect(target: &str) -> bool {
+    target.starts_with('/') && !target.starts_with("//") && !target.contains('\\')
+}
+
+fn username_from_email(email: &str) -> String {
+    let local = email.split('@').next().unwrap_or_default();
+    let username: String = local
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'))
+        .take(50)
+        .collect();
+    if username.is_empty() {
+        "user".to_string()
+    } else {
+        username.to_lowercase()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_service_provider_urls_follow_the_site_url() {
+        let sp = service_provider("https://example.com/");
+        assert_eq!(
+            sp.entity_id,
+            "https://example.com/api/v1/auth/saml/metadata"
+        );
+        assert_eq!(sp.acs_url, "https://example.com/api/v1/auth/saml/acs");
+    }
+
+    #[test]
+    fn test_only_local_paths_are_redirected_to() {
+        assert!(is_local_redirect("/admin"));
+        assert!(is_local_redirect("/posts?page=2"));
+        assert!(!is_local_redirect("//evil.example"));
+        assert!(!is_local_redirect("/\\evil.example"));
+        assert!(!is_local_redirect("https://evil.example"));
+        assert!(!is_local_redirect("admin"));
+    }
+
+    #[test]
+    fn test_validate_rejects_unusable_providers() {
+        let provider = |name: &str| SamlIdentityProvider {
+            certificates: vec![],
+            ..SamlIdentityProvider::new(
+                name.to_string(),
+                "https://idp.example.com".to_string(),
+                "https://idp.example.com/sso".to_string(),
+                String::new(),
+            )
+        };
+
+        assert!(SamlSsoConfig::default().validate().is_ok());
+        for name in ["", "Okta", "okta/eu"] {
+            let config = SamlSsoConfig {
+                providers: vec![provider(name)],
+            };
+            assert!(config.validate().is_err(), "{:?} was accepted", name);
+        }
+        // Without a certificate nothing the IdP sends could be verified
+        let config = SamlSsoConfig {
+            providers: vec![provider("okta")],
+        };
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_username_from_email() {
+        assert_eq!(
+            username_from_email("Jane.Doe+sso@example.com"),
+            "jane.doesso"
+        );
+        assert_eq!(username_from_email("@example.com"), "user");
+    }
+}
@@ -0,0 +1,790 @@
+//! Saved Views
+//!
+//! Named filter, sort and column configurations for the admin post,
+//! comment and media lists. A view belongs to the user who saved it and
+//! can be shared with roles; users holding one of those roles see it next
+//! to their own views but cannot change it.
+//!
+//! Views live in the `saved_views` table. Which view a user opens by
+//! default and the order their views are listed in are preferences kept
+//! in `users.meta` under `list_views`, keyed by resource. Preferences
+//! pointing at views that were deleted or unshared are ignored on read.
+
+use chrono::{DateTime, Utc};
+use rustpress_core::error::{Error, Result};
+use rustpress_database::filter::{FilterSchema, COMMENT_FILTERS, MEDIA_FILTERS, POST_FILTERS};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::types::Json;
+use sqlx::{FromRow, PgPool};
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+use uuid::Uuid;
+
+/// Longest accepted view name
+pub const MAX_VIEW_NAME_LENGTH: usize = 100;
+
+/// Most columns a view may list
+pub const MAX_VIEW_COLUMNS: usize = 30;
+
+/// Most views a user may save per list
+pub const MAX_VIEWS_PER_LIST: i64 = 50;
+
+/// Roles a view can be shared with
+const SHAREABLE_ROLES: [&str; 5] = [
+    "subscriber",
+    "contributor",
+    "author",
+    "editor",
+    "administrator",
+];
+
+const VIEW_COLUMNS: &str = "id, owner_id, resource, name, filter, sort_by, sort_order, columns, \
+     shared_roles, created_at, updated_at";
+
+/// Admin list a view applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ListResource {
+    Posts,
+    Comments,
+    Media,
+}
+
+impl ListResource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Posts => "posts",
+            Self::Comments => "comments",
+            Self::Media => "media",
+        }
+    }
+
+    /// Filter fields the list accepts
+    pub fn filters(&self) -> &'static FilterSchema {
+        match self {
+            Self::Posts => &POST_FILTERS,
+            Self::Comments => &COMMENT_FILTERS,
+            Self::Media => &MEDIA_FILTERS,
+        }
+    }
+
+    /// Columns the list can be sorted by
+    pub fn sort_fields(&self) -> &'static [&'static str] {
+        match self {
+            Self::Posts => &[
+                "created_at",
+                "updated_at",
+                "published_at",
+                "title",
+                "slug",
+                "status",
+                "comment_count",
+            ],
+            Self::Comments => &["created_at", "likes_count"],
+            Self::Media => &["created_at", "original_filename", "file_size", "mime_type"],
+        }
+    }
+}
+
+impl fmt::Display for ListResource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ListResource {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "posts" => Ok(Self::Posts),
+            "comments" => Ok(Self::Comments),
+            "media" => Ok(Self::Media),
+            _ => Err(Error::invalid_input(
+                "resource",
+                format!(
+                    "Unknown list '{}'; expected one of: posts, comments, media",
+                    s
+                ),
+            )),
+        }
+    }
+}
+
+/// Sort direction of a saved view
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ViewSortOrder {
+    Asc,
+    Desc,
+}
+
+impl ViewSortOrder {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Asc => "asc",
+            Self::Desc => "desc",
+        }
+    }
+}
+
+/// Name, configuration and sharing of a view as submitted
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SavedViewInput {
+    pub name: String,
+    /// Filter expression in the list endpoint's `filter` syntax
+    #[serde(default)]
+    pub filter: Option<String>,
+    #[serde(default)]
+    pub sort_by: Option<String>,
+    #[serde(default)]
+    pub sort_order: Option<ViewSortOrder>,
+    /// Visible columns, in display order
+    #[serde(default)]
+    pub columns: Vec<String>,
+    /// Roles whose users may also use the view
+    #[serde(default)]
+    pub shared_roles: Vec<String>,
+}
+
+impl SavedViewInput {
+    /// Validate against the list's filters and sort fields, trimming and
+    /// de-duplicating along the way
+    fn normalize(mut self, resource: ListResource) -> Result<Self> {
+        self.name = self.name.trim().to_string();
+        if self.name.is_empty() {
+            return Err(Error::invalid_input("name", "Name is required"));
+        }
+        if self.name.chars().count() > MAX_VIEW_NAME_LENGTH {
+            return Err(Error::invalid_input(
+                "name",
+                format!("Must be at most {} characters", MAX_VIEW_NAME_LENGTH),
+            ));
+        }
+
+        self.filter = self
+            .filter
+            .map(|filter| filter.trim().to_string())
+            .filter(|filter| !filter.is_empty());
+        if let Some(filter) = &self.filter {
+            resource.filters().parse(filter)?;
+        }
+
+        if let Some(sort_by) = &self.sort_by {
+            if !resource.sort_fields().contains(&sort_by.as_str()) {
+                return Err(Error::invalid_input(
+                    "sort_by",
+                    format!(
+                        "Cannot sort {} by '{}'; expected one of: {}",
+                        resource,
+                        sort_by,
+                        resource.sort_fields().join(", ")
+                    ),
+                ));
+            }
+        }
+
+        if self.columns.len() > MAX_VIEW_COLUMNS {
+            return Err(Error::invalid_input(
+                "columns",
+                format!("At most {} columns can be shown", MAX_VIEW_COLUMNS),
+            ));
+        }
+        let mut seen = HashSet::new();
+        for column in &self.columns {
+            if !is_identifier(column) {
+                return Err(Error::invalid_input(
+                    "columns",
+                    format!("Invalid column name '{}'", column),
+                ));
+            }
+            if !seen.insert(column.as_str()) {
+                return Err(Error::invalid_input(
+                    "columns",
+                    format!("Column '{}' is listed twice", column),
+                ));
+            }
+        }
+
+        let mut roles = Vec::new();
+        for role in &self.shared_roles {
+            let role = role.trim().to_lowercase();
+            if !SHAREABLE_ROLES.contains(&role.as_str()) {
+                return Err(Error::invalid_input(
+                    "shared_roles",
+                    format!(
+                        "Unknown role '{}'; expected one of: {}",
+                        role,
+                        SHAREABLE_ROLES.join(", ")
+                    ),
+                ));
+            }
+            if !roles.contains(&role) {
+                roles.push(role);
+            }
+        }
+        self.shared_roles = roles;
+
+        Ok(self)
+    }
+}
+
+fn is_identifier(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '.')
+}
+
+/// A saved view as returned to a user
+#[derive(Debug, Clone, Serialize)]
+pub struct SavedView {
+    pub id: Uuid,
+    pub resource: ListResource,
+    pub name: String,
+    pub filter: Option<String>,
+    pub sort_by: Option<String>,
+    pub sort_order: Option<ViewSortOrder>,
+    pub columns: Vec<String>,
+    pub shared_roles: Vec<String>,
+    pub owner_id: Uuid,
+    /// Whether the requesting user saved the view
+    pub owned: bool,
+    /// Whether the view opens by default for the requesting user
+    pub is_default: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Views available to a user on one list
+#[derive(Debug, Clone, Serialize)]
+pub struct SavedViewList {
+    pub resource: ListResource,
+    pub default_view_id: Option<Uuid>,
+    /// In the user's chosen order; views not yet ordered follow by name
+    pub views: Vec<SavedView>,
+}
+
+#[derive(Debug, FromRow)]
+struct SavedViewRow {
+    id: Uuid,
+    owner_id: Uuid,
+    resource: String,
+    name: String,
+    filter: Option<String>,
+    sort_by: Option<String>,
+    sort_order: Option<String>,
+    columns: Json<Vec<String>>,
+    shared_roles: Json<Vec<String>>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl SavedViewRow {
+    fn into_view(self, user_id: Uuid, default_view_id: Option<Uuid>) -> Result<SavedView> {
+        let sort_order = match self.sort_order.as_deref() {
+            Some("asc") => Some(ViewSortOrder::Asc),
+            Some("desc") => Some(ViewSortOrder::Desc),
+            _ => None,
+        };
+        Ok(SavedView {
+            id: self.id,
+            resource: self.resource.parse()?,
+            name: self.name,
+            filter: self.filter,
+            sort_by: self.sort_by,
+            sort_order,
+            columns: self.columns.0,
+            shared_roles: self.shared_roles.0,
+            owner_id: self.owner_id,
+            owned: self.owner_id == user_id,
+            is_default: default_view_id == Some(self.id),
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+        })
+    }
+
+    fn visible_to(&self, user: &ViewUser) -> bool {
+        self.owner_id == user.id || self.shared_roles.0.iter().any(|role| user.has_role(role))
+    }
+}
+
+/// Per-user preferences for one list, stored in `users.meta`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct ListPreferences {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    default: Option<Uuid>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    order: Vec<Uuid>,
+}
+
+/// Sort views by the user's order, then unordered views by name
+fn sort_views(views: &mut [SavedView], order: &[Uuid]) {
+    views.sort_by(|a, b| {
+        let rank = |view: &SavedView| order.iter().position(|id| *id == view.id);
+        match (rank(a), rank(b)) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+        }
+    });
+}
+
+/// The user views are listed for and changed by
+#[derive(Debug, Clone)]
+pub struct ViewUser {
+    pub id: Uuid,
+    pub roles: Vec<String>,
+}
+
+impl ViewUser {
+    pub fn new(id: Uuid, roles: Vec<String>) -> Self {
+        Self { id, roles }
+    }
+
+    fn has_role(&self, role: &str) -> bool {
+        self.roles.iter().any(|r| r == role)
+    }
+
+    fn is_admin(&self) -> bool {
+        self.has_role("administrator")
+    }
+}
+
+/// Saved views for admin lists
+pub struct SavedViewService {
+    pool: PgPool,
+}
+
+impl SavedViewService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Views the user saved or that were shared with one of their roles
+    pub async fn list(&self, user: &ViewUser, resource: ListResource) -> Result<SavedViewList> {
+        let rows: Vec<SavedViewRow> = sqlx::query_as(&format!(
+            "SELECT {} FROM saved_views \
+             WHERE resource = $1 AND (owner_id = $2 OR shared_roles ?| $3)",
+            VIEW_COLUMNS
+        ))
+        .bind(resource.as_str())
+        .bind(user.id)
+        .bind(&user.roles)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to list saved views", e))?;
+
+        let preferences = self.preferences(user.id, resource).await?;
+        let default_view_id = preferences
+            .default
+            .filter(|id| rows.iter().any(|row| row.id == *id));
+
+        let mut views = rows
+            .into_iter()
+            .map(|row| row.into_view(user.id, default_view_id))
+            .collect::<Result<Vec<_>>>()?;
+        sort_views(&mut views, &preferences.order);
+
+        Ok(SavedViewList {
+            resource,
+            default_view_id,
+            views,
+        })
+    }
+
+    /// A single view, if the user can see it
+    pub async fn get(&self, user: &ViewUser, id: Uuid) -> Result<SavedView> {
+        let row = self.visible_row(user, id).await?;
+        let resource = row.resource.parse()?;
+        let preferences = self.preferences(user.id, resource).await?;
+        row.into_view(user.id, preferences.default)
+    }
+
+    /// Save a new view owned by the user
+    pub async fn create(
+        &self,
+        user: &ViewUser,
+        resource: ListResource,
+        input: SavedViewInput,
+    ) -> Result<SavedView> {
+        let input = input.normalize(resource)?;
+        self.ensure_unique_name(user.id, resource, &input.name, None)
+            .await?;
+
+        let (count,): (i64,) = sqlx::query_as(
+            "SELECT COUNT(*) FROM saved_views WHERE owner_id = $1 AND resource = $2",
+        )
+        .bind(user.id)
+        .bind(resource.as_str())
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to count saved views", e))?;
+        if count >= MAX_VIEWS_PER_LIST {
+            return Err(Error::validation(format!(
+                "At most {} views can be saved per list",
+                MAX_VIEWS_PER_LIST
+            )));
+        }
+
+        let row: SavedViewRow = sqlx::query_as(&format!(
+            r#"
+            INSERT INTO saved_views
+                (id, owner_id, resource, name, filter, sort_by, sort_order, columns, shared_roles)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            RETURNING {}
+            "#,
+            VIEW_COLUMNS
+        ))
+        .bind(Uuid::new_v4())
+        .bind(user.id)
+        .bind(resource.as_str())
+        .bind(&input.name)
+        .bind(&input.filter)
+        .bind(&input.sort_by)
+        .bind(input.sort_order.map(|order| order.as_str()))
+        .bind(Json(&input.columns))
+        .bind(Json(&input.shared_roles))
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to save view", e))?;
+
+        row.into_view(user.id, None)
+    }
+
+    /// Replace a view's name, configuration and sharing (owner or administrator)
+    pub async fn update(
+        &self,
+        user: &ViewUser,
+        id: Uuid,
+        input: SavedViewInput,
+    ) -> Result<SavedView> {
+        let existing = self.editable_row(user, id).await?;
+        let resource: ListResource = existing.resource.parse()?;
+        let input = input.normalize(resource)?;
+        self.ensure_unique_name(existing.owner_id, resource, &input.name, Some(id))
+            .await?;
+
+        let row: SavedViewRow = sqlx::query_as(&format!(
+            r#"
+            UPDATE saved_views
+            SET name = $2, filter = $3, sort_by = $4, sort_order = $5, columns = $6,
+                shared_roles = $7, updated_at = NOW()
+            WHERE id = $1
+            RETURNING {}
+            "#,
+            VIEW_COLUMNS
+        ))
+        .bind(id)
+        .bind(&input.name)
+        .bind(&input.filter)
+        .bind(&input.sort_by)
+        .bind(input.sort_order.map(|order| order.as_str()))
+        .bind(Json(&input.columns))
+        .bind(Json(&input.shared_roles))
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to update saved view", e))?;
+
+        let preferences = self.preferences(user.id, resource).await?;
+        row.into_view(user.id, preferences.default)
+    }
+
+    /// Delete a view (owner or administrator)
+    pub async fn delete(&self, user: &ViewUser, id: Uuid) -> Result<()> {
+        self.editable_row(user, id).await?;
+
+        sqlx::query("DELETE FROM saved_views WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to delete saved view", e))?;
+
+        Ok(())
+    }
+
+    /// Choose the view a list opens with, or clear the choice
+    pub async fn set_default(
+        &self,
+        user: &ViewUser,
+        resource: ListResource,
+        view_id: Option<Uuid>,
+    ) -> Result<SavedViewList> {
+        if let Some(view_id) = view_id {
+            self.ensure_visible_on(user, resource, &[view_id]).await?;
+        }
+
+        let mut preferences = self.preferences(user.id, resource).await?;
+        preferences.default = view_id;
+        self.save_preferences(user.id, resource, &preferences)
+            .await?;
+        self.list(user, resource).await
+    }
+
+    /// Set the order views are listed in; views left out follow by name
+    pub async fn reorder(
+        &self,
+        user: &ViewUser,
+        resource: ListResource,
+        view_ids: Vec<Uuid>,
+    ) -> Result<SavedViewList> {
+        let mut order = Vec::with_capacity(view_ids.len());
+        for id in view_ids {
+            if order.contains(&id) {
+                return Err(Error::invalid_input(
+                    "view_ids",
+                    format!("View {} is listed twice", id),
+                ));
+            }
+            order.push(id);
+        }
+        self.ensure_visible_on(user, resource, &order).await?;
+
+        let mut preferences = self.preferences(user.id, resource).await?;
+        preferences.order = order;
+        self.save_preferences(user.id, resource, &preferences)
+            .await?;
+        self.list(user, resource).await
+    }
+
+    async fn load_row(&self, id: Uuid) -> Result<Option<SavedViewRow>> {
+        sqlx::query_as(&format!(
+            "SELECT {} FROM saved_views WHERE id = $1",
+            VIEW_COLUMNS
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load saved view", e))
+    }
+
+    /// Views the user cannot see are reported as missing
+    async fn visible_row(&self, user: &ViewUser, id: Uuid) -> Result<SavedViewRow> {
+        self.load_row(id)
+            .await?
+            .filter(|row| row.visible_to(user) || user.is_admin())
+            .ok_or_else(|| Error::not_found("Saved view", id.to_string()))
+    }
+
+    async fn editable_row(&self, user: &ViewUser, id: Uuid) -> Result<SavedViewRow> {
+        let row = self.visible_row(user, id).await?;
+        if row.owner_id != user.id && !user.is_admin() {
+            return Err(Error::forbidden("change a view shared with you"));
+        }
+        Ok(row)
+    }
+
+    async fn ensure_visible_on(
+        &self,
+        user: &ViewUser,
+        resource: ListResource,
+        ids: &[Uuid],
+    ) -> Result<()> {
+        for id in ids {
+            let row = self.visible_row(user, *id).await?;
+            if row.resource != resource.as_str() {
+                return Err(Error::invalid_input(
+                    "view_ids",
+                    format!("View {} belongs to the {} list", id, row.resource),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    async fn ensure_unique_name(
+        &self,
+        owner_id: Uuid,
+        resource: ListResource,
+        name: &str,
+        except: Option<Uuid>,
+    ) -> Result<()> {
+        let taken: Option<(Uuid,)> = sqlx::query_as(
+            "SELECT id FROM saved_views WHERE owner_id = $1 AND resource = $2 AND name = $3",
+        )
+        .bind(owner_id)
+        .bind(resource.as_str())
+        .bind(name)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to check view name", e))?;
+
+        match taken {
+            Some((id,)) if Some(id) != except => Err(Error::Duplicate {
+                entity_type: "Saved view".to_string(),
+                field: "name".to_string(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    async fn preferences(&self, user_id: Uuid, resource: ListResource) -> Result<ListPreferences> {
+        let row: Option<(Option<Value>,)> =
+            sqlx::query_as("SELECT meta -> 'list_views' -> $2::text FROM users WHERE id = $1")
+                .bind(user_id)
+                .bind(resource.as_str())
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(|e| Error::database_with_source("Failed to load view preferences", e))?;
+
+        Ok(row
+            .and_then(|(value,)| value)
+            .and_then(|value| serde_json::from_value(value).ok())
+            .unwrap_or_default())
+    }
+
+    async fn save_preferences(
+        &self,
+        user_id: Uuid,
+        resource: ListResource,
+        preferences: &ListPreferences,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE users
+            SET meta = jsonb_set(
+                    COALESCE(meta, '{}'::jsonb),
+                    '{list_views}',
+                    COALESCE(meta -> 'list_views', '{}'::jsonb) || jsonb_build_object($2::text, $3::jsonb)
+                ),
+                updated_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(user_id)
+        .bind(resource.as_str())
+        .bind(Json(preferences))
+        .execute(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to save view preferences", e))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(name: &str) -> SavedViewInput {
+        SavedViewInput {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn view(name: &str) -> SavedView {
+        SavedView {
+            id: Uuid::new_v4(),
+            resource: ListResource::Posts,
+            name: name.to_string(),
+            filter: None,
+            sort_by: None,
+            sort_order: None,
+            columns: Vec::new(),
+            shared_roles: Vec::new(),
+            owner_id: Uuid::nil(),
+            owned: true,
+            is_default: false,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_normalize_validates_against_list() {
+        let normalized = SavedViewInput {
+            filter: Some("  status:published AND category:rust ".to_string()),
+            sort_by: Some("published_at".to_string()),
+            columns: vec!["title".to_string(), "author.name".to_string()],
+            shared_roles: vec!["Editor".to_string(), "editor".to_string()],
+            ..input("  Published Rust ")
+        }
+        .normalize(ListResource::Posts)
+        .unwrap();
+        assert_eq!(normalized.name, "Published Rust");
+        assert_eq!(
+            normalized.filter.as_deref(),
+            Some("status:published AND category:rust")
+        );
+        assert_eq!(normalized.shared_roles, vec!["editor".to_string()]);
+
+        // Posts fields are not comment fields
+        let err = SavedViewInput {
+            filter: Some("category:rust".to_string()),
+            ..input("Rust")
+        }
+        .normalize(ListResource::Comments)
+        .unwrap_err();
+        assert!(err.to_string().contains("category"), "{}", err);
+
+        let err = SavedViewInput {
+            sort_by: Some("title; DROP TABLE posts".to_string()),
+            ..input("Bad sort")
+        }
+        .normalize(ListResource::Posts)
+        .unwrap_err();
+        assert!(err.to_string().contains("sort"), "{}", err);
+    }
+
+    #[test]
+    fn test_normalize_rejects_bad_fields() {
+        assert!(input("   ").normalize(ListResource::Media).is_err());
+        assert!(input(&"x".repeat(MAX_VIEW_NAME_LENGTH + 1))
+            .normalize(ListResource::Media)
+            .is_err());
+        assert!(SavedViewInput {
+            columns: vec!["title".to_string(), "title".to_string()],
+            ..input("Twice")
+        }
+        .normalize(ListResource::Media)
+        .is_err());
+        assert!(SavedViewInput {
+            columns: vec!["Title Case".to_string()],
+            ..input("Spaces")
+        }
+        .normalize(ListResource::Media)
+        .is_err());
+        assert!(SavedViewInput {
+            shared_roles: vec!["everyone".to_string()],
+            ..input("Roles")
+        }
+        .normalize(ListResource::Media)
+        .is_err());
+        assert!(SavedViewInput {
+            filter: Some("   ".to_string()),
+            ..input("Empty filter")
+        }
+        .normalize(ListResource::Media)
+        .unwrap()
+        .filter
+        .is_none());
+    }
+
+    #[test]
+    fn test_sort_views_follows_user_order() {
+        let mut views = vec![view("beta"), view("Alpha"), view("gamma"), view("delta")];
+        let order = vec![views[2].id, views[3].id];
+        sort_views(&mut views, &order);
+
+        let names: Vec<&str> = views.iter().map(|v| v.name.as_str()).collect();
+        assert_eq!(names, vec!["gamma", "delta", "Alpha", "beta"]);
+    }
+
+    #[test]
+    fn test_resource_parsing() {
+        assert_eq!(
+            "media".parse::<ListResource>().unwrap(),
+            ListResource::Media
+        );
+        assert!("pages".parse::<ListResource>().is_err());
+        assert!(ListResource::Comments
+            .filters()
+            .parse("status:spam")
+            .is_ok());
+    }
+}
@@ -0,0 +1,1585 @@
+//! Full-text Search
+//!
+//! Published posts, pages and media are kept in a search index behind a
+//! [`SearchBackend`]. PostgreSQL full-text search over the
+//! `search_documents` table works out of the box; Meilisearch and
+//! Elasticsearch can be configured under `[search]` instead.
+//!
+//! The index follows the change feed: every `change.post.*` and
+//! `change.media.*` event reloads the row and indexes or removes it. All
+//! nodes receive every change, so backends must tolerate the same document
+//! being written more than once and out of order; both the PostgreSQL table
+//! and Elasticsearch keep the copy with the newest `updated_at`. After a
+//! `change.resync` rows updated since the last sync are indexed again.
+//! Hard deletes missed while the feed was down need a full reindex.
+//!
+//! Documents keep the site of their source row, and searches only match
+//! documents of the site the request serves.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use reqwest::Method;
+use rustpress_content::sanitize::{escape_html, strip_tags, unescape_html};
+use rustpress_core::config::{SearchBackend as SearchBackendKind, SearchConfig};
+use rustpress_core::error::{Error, Result};
+use rustpress_core::http::HttpClient;
+use rustpress_core::tenant::current_site;
+use rustpress_events::subscriber::SubscriberConfig;
+use rustpress_events::{EventBus, EventType, Subscriber};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use super::change_feed::{RowChange, RESYNC_EVENT};
+
+/// Rows loaded per query while reindexing
+const REINDEX_BATCH: i64 = 200;
+
+/// Longest query accepted, in characters
+const MAX_QUERY_LENGTH: usize = 256;
+
+/// Highlight markers used by every backend; replaced by `<mark>` once the
+/// surrounding text is escaped
+const HIGHLIGHT_START: char = '\u{1}';
+const HIGHLIGHT_END: char = '\u{2}';
+
+/// Kind of indexed item
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DocumentKind {
+    Post,
+    Page,
+    Media,
+}
+
+impl DocumentKind {
+    pub const ALL: [DocumentKind; 3] = [Self::Post, Self::Page, Self::Media];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Post => "post",
+            Self::Page => "page",
+            Self::Media => "media",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim() {
+            "post" | "posts" => Some(Self::Post),
+            "page" | "pages" => Some(Self::Page),
+            "media" | "attachment" => Some(Self::Media),
+            _ => None,
+        }
+    }
+
+    /// Parse a comma-separated list; unknown kinds are rejected
+    pub fn parse_list(value: &str) -> Result<Vec<Self>> {
+        value
+            .split(',')
+            .filter(|kind| !kind.trim().is_empty())
+            .map(|kind| {
+                Self::parse(kind).ok_or_else(|| {
+                    Error::invalid_input("type", format!("Unknown search type '{}'", kind.trim()))
+                })
+            })
+            .collect()
+    }
+}
+
+/// A searchable item as sent to the backend
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchDocument {
+    pub id: Uuid,
+    pub kind: DocumentKind,
+    pub title: String,
+    pub excerpt: Option<String>,
+    /// Plain text, without markup
+    pub body: String,
+    pub slug: Option<String>,
+    pub published_at: Option<DateTime<Utc>>,
+    /// `updated_at` of the source row; newer copies win
+    pub updated_at: DateTime<Utc>,
+    /// Site of the source row; `None` for the main site
+    pub site_id: Option<Uuid>,
+}
+
+/// A search request
+#[derive(Debug, Clone)]
+pub struct SearchQuery {
+    pub q: String,
+    /// Kinds to search; all when empty
+    pub kinds: Vec<DocumentKind>,
+    pub page: u32,
+    pub per_page: u32,
+    /// Site to search, the current one for [`SearchQuery::new`]
+    pub site_id: Option<Uuid>,
+}
+
+impl SearchQuery {
+    pub fn new(q: impl Into<String>) -> Self {
+        Self {
+            q: q.into(),
+            kinds: Vec::new(),
+            page: 1,
+            per_page: 20,
+            site_id: current_site(),
+        }
+    }
+
+    pub fn kinds(mut self, kinds: Vec<DocumentKind>) -> Self {
+        self.kinds = kinds;
+        self
+    }
+
+    pub fn page(mut self, page: u32, per_page: u32) -> Self {
+        self.page = page.max(1);
+        self.per_page = per_page.clamp(1, 100);
+        self
+    }
+
+    pub fn offset(&self) -> u32 {
+        (self.page - 1) * self.per_page
+    }
+
+    /// Kinds to search, all of them when none were asked for
+    pub fn effective_kinds(&self) -> Vec<DocumentKind> {
+        if self.kinds.is_empty() {
+            DocumentKind::ALL.to_vec()
+        } else {
+            self.kinds.clone()
+        }
+    }
+}
+
+/// A matching item
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchHit {
+    pub id: Uuid,
+    #[serde(rename = "type")]
+    pub kind: DocumentKind,
+    pub title: String,
+    pub slug: Option<String>,
+    pub excerpt: Option<String>,
+    /// Escaped body fragment with matches wrapped in `<mark>`
+    pub highlight: Option<String>,
+    pub published_at: Option<DateTime<Utc>>,
+    pub score: f64,
+}
+
+/// One page of matches
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchResults {
+    pub results: Vec<SearchHit>,
+    pub total: u64,
+    pub page: u32,
+    pub per_page: u32,
+    pub total_pages: u32,
+    pub backend: &'static str,
+}
+
+impl SearchResults {
+    fn new(
+        query: &SearchQuery,
+        backend: &'static str,
+        results: Vec<SearchHit>,
+        total: u64,
+    ) -> Self {
+        Self {
+            results,
+            total,
+            page: query.page,
+            per_page: query.per_page,
+            total_pages: total.div_ceil(query.per_page as u64) as u32,
+            backend,
+        }
+    }
+}
+
+/// Escape a fragment and turn the backend's highlight markers into `<mark>`
+fn render_highlight(fragment: &str) -> String {
+    escape_html(fragment)
+        .replace(HIGHLIGHT_START, "<mark>")
+        .replace(HIGHLIGHT_END, "</mark>")
+}
+
+/// Plain text of stored HTML
+fn plain_text(html: &str) -> String {
+    unescape_html(&strip_tags(html))
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Stores and queries search documents
+#[async_trait]
+pub trait SearchBackend: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Create the index or its settings if needed
+    async fn prepare(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Add or replace documents, keeping newer copies already indexed
+    async fn index(&self, documents: &[SearchDocument]) -> Result<()>;
+
+    async fn remove(&self, ids: &[Uuid]) -> Result<()>;
+
+    async fn search(&self, query: &SearchQuery) -> Result<SearchResults>;
+
+    /// Titles on the site starting with or matching the prefix
+    async fn suggest(&self, prefix: &str, site_id: Option<Uuid>, limit: u32)
+        -> Result<Vec<String>>;
+
+    /// Remove every document
+    async fn clear(&self) -> Result<()>;
+
+    /// Number of indexed documents
+    async fn count(&self) -> Result<u64>;
+}
+
+/// PostgreSQL full-text search over `search_documents`
+pub struct PostgresBackend {
+    pool: PgPool,
+    /// Text search configuration, e.g. `english`
+    language: String,
+}
+
+impl PostgresBackend {
+    pub fn new(pool: PgPool, language: impl Into<String>) -> Self {
+        Self {
+            pool,
+            language: language.into(),
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct PostgresHit {
+    id: Uuid,
+    kind: String,
+    title: String,
+    slug: Option<String>,
+    excerpt: Option<String>,
+    highlight: Option<String>,
+    published_at: Option<DateTime<Utc>>,
+    score: f32,
+    total: i64,
+}
+
+#[async_trait]
+impl SearchBackend for PostgresBackend {
+    fn name(&self) -> &'static str {
+        "postgres"
+    }
+
+    async fn prepare(&self) -> Result<()> {
+        sqlx::query_scalar::<_, String>("SELECT $1::regconfig::text")
+            .bind(&self.language)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| {
+                Error::database_with_source(
+                    format!("Unknown text search configuration '{}'", self.language),
+                    e,
+                )
+            })?;
+        Ok(())
+    }
+
+    async fn index(&self, documents: &[SearchDocument]) -> Result<()> {
+        if documents.is_empty() {
+            return Ok(());
+        }
+        let mut ids = Vec::with_capacity(documents.len());
+        let mut kinds = Vec::with_capacity(documents.len());
+        let mut titles = Vec::with_capacity(documents.len());
+        let mut excerpts = Vec::with_capacity(documents.len());
+        let mut bodies = Vec::with_capacity(documents.len());
+        let mut slugs = Vec::with_capacity(documents.len());
+        let mut published = Vec::with_capacity(documents.len());
+        let mut updated = Vec::with_capacity(documents.len());
+        let mut sites = Vec::with_capacity(documents.len());
+        for document in documents {
+            ids.push(document.id);
+            kinds.push(document.kind.as_str());
+            titles.push(document.title.as_str());
+            excerpts.push(document.excerpt.as_deref());
+            bodies.push(document.body.as_str());
+            slugs.push(document.slug.as_deref());
+            published.push(document.published_at);
+            updated.push(document.updated_at);
+            sites.push(document.site_id);
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO search_documents
+                (id, kind, title, excerpt, body, slug, published_at, source_updated_at,
+                 site_id, language)
+            SELECT d.*, $10::regconfig
+            FROM UNNEST(
+                $1::uuid[], $2::varchar[], $3::text[], $4::text[], $5::text[],
+                $6::varchar[], $7::timestamptz[], $8::timestamptz[], $9::uuid[]
+            ) AS d
+            ON CONFLICT (id) DO UPDATE SET
+                kind = EXCLUDED.kind,
+                title = EXCLUDED.title,
+                excerpt = EXCLUDED.excerpt,
+                body = EXCLUDED.body,
+                slug = EXCLUDED.slug,
+                published_at = EXCLUDED.published_at,
+                source_updated_at = EXCLUDED.source_updated_at,
+                site_id = EXCLUDED.site_id,
+                language = EXCLUDED.language,
+                indexed_at = NOW()
+            WHERE search_documents.source_updated_at <= EXCLUDED.source_updated_at
+            "#,
+        )
+        .bind(&ids)
+        .bind(&kinds)
+        .bind(&titles)
+        .bind(&excerpts)
+        .bind(&bodies)
+        .bind(&slugs)
+        .bind(&published)
+        .bind(&updated)
+        .bind(&sites)
+        .bind(&self.language)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to index search documents", e))?;
+        Ok(())
+    }
+
+    async fn remove(&self, ids: &[Uuid]) -> Result<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        sqlx::query("DELETE FROM search_documents WHERE id = ANY($1)")
+            .bind(ids)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to remove search documents", e))?;
+        Ok(())
+    }
+
+    async fn search(&self, query: &SearchQuery) -> Result<SearchResults> {
+        let kinds: Vec<&str> = query
+            .effective_kinds()
+            .iter()
+            .map(DocumentKind::as_str)
+            .collect();
+        let options = format!(
+            "StartSel={}, StopSel={}, MaxWords=30, MinWords=10, MaxFragments=1",
+            HIGHLIGHT_START, HIGHLIGHT_END
+        );
+
+        let rows: Vec<PostgresHit> = sqlx::query_as(
+            r#"
+            WITH q AS (SELECT websearch_to_tsquery($1::regconfig, $2) AS query)
+            SELECT d.id, d.kind, d.title, d.slug, d.excerpt,
+                   ts_headline(d.language, d.body, q.query, $3) AS highlight,
+                   d.published_at,
+                   ts_rank_cd(d.search_vector, q.query) AS score,
+                   COUNT(*) OVER () AS total
+            FROM search_documents d, q
+            WHERE d.search_vector @@ q.query
+              AND d.kind = ANY($4)
+              AND d.site_id IS NOT DISTINCT FROM $7
+            ORDER BY score DESC, d.published_at DESC NULLS LAST
+            LIMIT $5 OFFSET $6
+            "#,
+        )
+        .bind(&self.language)
+        .bind(&query.q)
+        .bind(&options)
+        .bind(&kinds)
+        .bind(query.per_page as i64)
+        .bind(query.offset() as i64)
+        .bind(query.site_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Search failed", e))?;
+
+        let total = match rows.first() {
+            Some(row) => row.total as u64,
+            // Past the last page the window count is not available
+            None if query.page > 1 => sqlx::query_scalar::<_, i64>(
+                r#"
+                SELECT COUNT(*) FROM search_documents
+                WHERE search_vector @@ websearch_to_tsquery($1::regconfig, $2)
+                  AND kind = ANY($3)
+                  AND site_id IS NOT DISTINCT FROM $4
+                "#,
+            )
+            .bind(&self.language)
+            .bind(&query.q)
+            .bind(&kinds)
+            .bind(query.site_id)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Search count failed", e))?
+                as u64,
+            None => 0,
+        };
+
+        let hits = rows
+            .into_iter()
+            .filter_map(|row| {
+                Some(SearchHit {
+                    id: row.id,
+                    kind: DocumentKind::parse(&row.kind)?,
+                    title: row.title,
+                    slug: row.slug,
+                    excerpt: row.excerpt,
+                    highlight: row
+                        .highlight
+                        .filter(|h| h.contains(HIGHLIGHT_START))
+                        .map(|h| render_highlight(&h)),
+                    published_at: row.published_at,
+                    score: row.score as f64,
+                })
+            })
+            .collect();
+        Ok(SearchResults::new(query, self.name(), hits, total))
+    }
+
+    async fn suggest(
+        &self,
+        prefix: &str,
+        site_id: Option<Uuid>,
+        limit: u32,
+    ) -> Result<Vec<String>> {
+        let pattern = format!(
+            "{}%",
+            prefix
+                .to_lowercase()
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_")
+        );
+        sqlx::query_scalar(
+            r#"
+            SELECT title FROM (
+                SELECT DISTINCT title FROM search_documents
+                WHERE lower(title) LIKE $1 AND site_id IS NOT DISTINCT FROM $3
+                ORDER BY title
+                LIMIT $2
+            ) t
+            "#,
+        )
+        .bind(&pattern)
+        .bind(limit as i64)
+        .bind(site_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Suggestions failed", e))
+    }
+
+    async fn clear(&self) -> Result<()> {
+        sqlx::query("DELETE FROM search_documents")
+            .execute(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to clear search documents", e))?;
+        Ok(())
+    }
+
+    async fn count(&self) -> Result<u64> {
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM search_documents")
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to count search documents", e))?;
+        Ok(count as u64)
+    }
+}
+
+/// Call a search server, returning the JSON body of a successful response
+///
+/// Statuses in `allowed` are returned as `None` rather than failing.
+async fn call(
+    client: &HttpClient,
+    request: reqwest::RequestBuilder,
+    backend: &str,
+    allowed: &[u16],
+) -> Result<Option<Value>> {
+    let response = client.send(request).await?;
+    let status = response.status();
+    if allowed.contains(&status.as_u16()) {
+        return Ok(None);
+    }
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(Error::internal(format!(
+            "{} returned {}: {}",
+            backend,
+            status,
+            body.chars().take(200).collect::<String>()
+        )));
+    }
+    let body = response
+        .json()
+        .await
+        .map_err(|e| Error::internal(format!("Invalid {} response: {}", backend, e)))?;
+    Ok(Some(body))
+}
+
+/// Key the remote backends filter sites on; the main site has the nil UUID
+fn site_key(site_id: Option<Uuid>) -> String {
+    site_id.unwrap_or_else(Uuid::nil).to_string()
+}
+
+/// Timestamps are sent as Unix seconds so they sort and filter natively
+fn document_json(document: &SearchDocument) -> Value {
+    json!({
+        "id": document.id,
+        "kind": document.kind,
+        "title": document.title,
+        "excerpt": document.excerpt,
+        "body": document.body,
+        "slug": document.slug,
+        "published_at": document.published_at.map(|t| t.timestamp()),
+        "updated_at": document.updated_at.timestamp(),
+        "site": site_key(document.site_id),
+    })
+}
+
+/// Build a hit from a stored document and an optional highlighted fragment
+fn hit_from_source(
+    source: &Value,
+    fallback_id: Option<&str>,
+    score: f64,
+    highlight: Option<&str>,
+) -> Option<SearchHit> {
+    let id = source
+        .get("id")
+        .and_then(Value::as_str)
+        .or(fallback_id)
+        .and_then(|id| Uuid::parse_str(id).ok())?;
+    let text = |field: &str| {
+        source
+            .get(field)
+            .and_then(Value::as_str)
+            .map(str::to_string)
+    };
+    Some(SearchHit {
+        id,
+        kind: DocumentKind::parse(source.get("kind")?.as_str()?)?,
+        title: text("title").unwrap_or_default(),
+        slug: text("slug"),
+        excerpt: text("excerpt"),
+        highlight: highlight
+            .filter(|h| h.contains(HIGHLIGHT_START))
+            .map(render_highlight),
+        published_at: source
+            .get("published_at")
+            .and_then(Value::as_i64)
+            .and_then(|t| DateTime::from_timestamp(t, 0)),
+        score,
+    })
+}
+
+/// Meilisearch index
+pub struct MeilisearchBackend {
+    client: HttpClient,
+    url: String,
+    index: String,
+    api_key: Option<String>,
+}
+
+impl MeilisearchBackend {
+    pub fn new(
+        client: HttpClient,
+        url: &str,
+        index: impl Into<String>,
+        api_key: Option<String>,
+    ) -> Self {
+        Self {
+            client,
+            url: url.trim_end_matches('/').to_string(),
+            index: index.into(),
+            api_key,
+        }
+    }
+
+    fn request(&self, method: Method, path: &str) -> reqwest::RequestBuilder {
+        self.authorized(
+            method,
+            format!("{}/indexes/{}{}", self.url, self.index, path),
+        )
+    }
+
+    fn authorized(&self, method: Method, url: String) -> reqwest::RequestBuilder {
+        let request = self.client.request(method, url);
+        match &self.api_key {
+            Some(key) => request.bearer_auth(key),
+            None => request,
+        }
+    }
+
+    /// Meilisearch applies writes as tasks; a document may briefly be
+    /// missing from results after it is accepted
+    async fn call(
+        &self,
+        request: reqwest::RequestBuilder,
+        allowed: &[u16],
+    ) -> Result<Option<Value>> {
+        call(&self.client, request, "Meilisearch", allowed).await
+    }
+
+    fn filter(query: &SearchQuery) -> String {
+        let kinds: Vec<&str> = query
+            .effective_kinds()
+            .iter()
+            .map(DocumentKind::as_str)
+            .collect();
+        format!(
+            "kind IN [{}] AND site = \"{}\"",
+            kinds.join(", "),
+            site_key(query.site_id)
+        )
+    }
+}
+
+#[async_trait]
+impl SearchBackend for MeilisearchBackend {
+    fn name(&self) -> &'static str {
+        "meilisearch"
+    }
+
+    async fn prepare(&self) -> Result<()> {
+        self.call(
+            self.authorized(Method::POST, format!("{}/indexes", self.url))
+                .json(&json!({ "uid": self.index, "primaryKey": "id" })),
+            &[],
+        )
+        .await?;
+        self.call(
+            self.request(Method::PATCH, "/settings").json(&json!({
+                "searchableAttributes": ["title", "excerpt", "body"],
+                "filterableAttributes": ["kind", "site"],
+                "sortableAttributes": ["published_at"],
+            })),
+            &[],
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn index(&self, documents: &[SearchDocument]) -> Result<()> {
+        if documents.is_empty() {
+            return Ok(());
+        }
+        let documents: Vec<Value> = documents.iter().map(document_json).collect();
+        self.call(
+            self.request(Method::POST, "/documents?primaryKey=id")
+                .json(&documents),
+            &[],
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn remove(&self, ids: &[Uuid]) -> Result<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        self.call(
+            self.request(Method::POST, "/documents/delete-batch")
+                .json(ids),
+            &[],
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn search(&self, query: &SearchQuery) -> Result<SearchResults> {
+        let body = self
+            .call(
+                self.request(Method::POST, "/search").json(&json!({
+                    "q": query.q,
+                    "offset": query.offset(),
+                    "limit": query.per_page,
+                    "filter": Self::filter(query),
+                    "attributesToCrop": ["body"],
+                    "cropLength": 30,
+                    "attributesToHighlight": ["body"],
+                    "highlightPreTag": HIGHLIGHT_START.to_string(),
+                    "highlightPostTag": HIGHLIGHT_END.to_string(),
+                    "showRankingScore": true,
+                })),
+                &[404],
+            )
+            .await?
+            .unwrap_or_default();
+
+        let hits = body
+            .get("hits")
+            .and_then(Value::as_array)
+            .map(|hits| {
+                hits.iter()
+                    .filter_map(|hit| {
+                        let highlight = hit.pointer("/_formatted/body").and_then(Value::as_str);
+                        let score = hit
+                            .get("_rankingScore")
+                            .and_then(Value::as_f64)
+                            .unwrap_or_default();
+                        hit_from_source(hit, None, score, highlight)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let total = body
+            .get("estimatedTotalHits")
+            .and_then(Value::as_u64)
+            .unwrap_or_default();
+        Ok(SearchResults::new(query, self.name(), hits, total))
+    }
+
+    async fn suggest(
+        &self,
+        prefix: &str,
+        site_id: Option<Uuid>,
+        limit: u32,
+    ) -> Result<Vec<String>> {
+        let body = self
+            .call(
+                self.request(Method::POST, "/search").json(&json!({
+                    "q": prefix,
+                    "limit": limit,
+                    "filter": format!("site = \"{}\"", site_key(site_id)),
+                    "attributesToSearchOn": ["title"],
+                    "attributesToRetrieve": ["title"],
+                })),
+                &[404],
+            )
+            .await?
+            .unwrap_or_default();
+        Ok(titles(body.get("hits"), |hit| hit.get("title")))
+    }
+
+    async fn clear(&self) -> Result<()> {
+        self.call(self.request(Method::DELETE, "/documents"), &[404])
+            .await?;
+        Ok(())
+    }
+
+    async fn count(&self) -> Result<u64> {
+        let body = self
+            .call(self.request(Method::GET, "/stats"), &[404])
+            .await?
+            .unwrap_or_default();
+        Ok(body
+            .get("numberOfDocuments")
+            .and_then(Value::as_u64)
+            .unwrap_or_default())
+    }
+}
+
+/// Distinct titles from a list of hits
+fn titles(hits: Option<&Value>, title: impl Fn(&Value) -> Option<&Value>) -> Vec<String> {
+    let mut titles: Vec<String> = Vec::new();
+    for hit in hits.and_then(Value::as_array).into_iter().flatten() {
+        if let Some(t) = title(hit).and_then(Value::as_str) {
+            if !titles.iter().any(|existing| existing == t) {
+                titles.push(t.to_string());
+            }
+        }
+    }
+    titles
+}
+
+/// Elasticsearch (or OpenSearch) index
+pub struct ElasticsearchBackend {
+    client: HttpClient,
+    url: String,
+    index: String,
+    api_key: Option<String>,
+}
+
+impl ElasticsearchBackend {
+    pub fn new(
+        client: HttpClient,
+        url: &str,
+        index: impl Into<String>,
+        api_key: Option<String>,
+    ) -> Self {
+        Self {
+            client,
+            url: url.trim_end_matches('/').to_string(),
+            index: index.into(),
+            api_key,
+        }
+    }
+
+    fn request(&self, method: Method, path: &str) -> reqwest::RequestBuilder {
+        let request = self
+            .client
+            .request(method, format!("{}/{}{}", self.url, self.index, path));
+        match &self.api_key {
+            Some(key) => request.header("Authorization", format!("ApiKey {}", key)),
+            None => request,
+        }
+    }
+
+    async fn call(
+        &self,
+        request: reqwest::RequestBuilder,
+        allowed: &[u16],
+    ) -> Result<Option<Value>> {
+        call(&self.client, request, "Elasticsearch", allowed).await
+    }
+
+    /// Send bulk actions; version conflicts (an older copy) and deletes of
+    /// missing documents are not errors
+    async fn bulk(&self, lines: Vec<Value>) -> Result<()> {
+        let mut body = String::new();
+        for line in lines {
+            body.push_str(&line.to_string());
+            body.push('\n');
+        }
+        let response = self
+            .call(
+                self.request(Method::POST, "/_bulk")
+                    .header("Content-Type", "application/x-ndjson")
+                    .body(body),
+                &[],
+            )
+            .await?
+            .unwrap_or_default();
+        if response.get("errors").and_then(Value::as_bool) != Some(true) {
+            return Ok(());
+        }
+        let failed = response
+            .get("items")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|item| item.as_object()?.values().next())
+            .find(|result| {
+                let status = result.get("status").and_then(Value::as_u64).unwrap_or(500);
+                status >= 300 && status != 404 && status != 409
+            });
+        match failed {
+            Some(result) => Err(Error::internal(format!(
+                "Elasticsearch bulk request failed: {}",
+                result.get("error").unwrap_or(result)
+            ))),
+            None => Ok(()),
+        }
+    }
+}
+
+#[async_trait]
+impl SearchBackend for ElasticsearchBackend {
+    fn name(&self) -> &'static str {
+        "elasticsearch"
+    }
+
+    async fn prepare(&self) -> Result<()> {
+        // 400 is returned when the index already exists
+        self.call(
+            self.request(Method::PUT, "").json(&json!({
+                "mappings": {
+                    "properties": {
+                        "kind": { "type": "keyword" },
+                        "site": { "type": "keyword" },
+                        "title": { "type": "text" },
+                        "excerpt": { "type": "text" },
+                        "body": { "type": "text" },
+                        "slug": { "type": "keyword" },
+                        "published_at": { "type": "date", "format": "epoch_second" },
+                        "updated_at": { "type": "date", "format": "epoch_second" },
+                    }
+                }
+            })),
+            &[400],
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn index(&self, documents: &[SearchDocument]) -> Result<()> {
+        if documents.is_empty() {
+            return Ok(());
+        }
+        let mut lines = Vec::with_capacity(documents.len() * 2);
+        for document in documents {
+            // External versions keep the newest copy when nodes race
+            lines.push(json!({
+                "index": {
+                    "_id": document.id,
+                    "version": document.updated_at.timestamp_micros(),
+                    "version_type": "external_gte",
+                }
+            }));
+            lines.push(document_json(document));
+        }
+        self.bulk(lines).await
+    }
+
+    async fn remove(&self, ids: &[Uuid]) -> Result<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        self.bulk(
+            ids.iter()
+                .map(|id| json!({ "delete": { "_id": id } }))
+                .collect(),
+        )
+        .await
+    }
+
+    async fn search(&self, query: &SearchQuery) -> Result<SearchResults> {
+        let kinds: Vec<&str> = query
+            .effective_kinds()
+            .iter()
+            .map(DocumentKind::as_str)
+            .collect();
+        let body = self
+            .call(
+                self.request(Method::POST, "/_search").json(&json!({
+                    "from": query.offset(),
+                    "size": query.per_page,
+                    "track_total_hits": true,
+                    "query": {
+                        "bool": {
+                            "must": {
+                                "multi_match": {
+                                    "query": query.q,
+                                    "fields": ["title^3", "excerpt^2", "body"],
+                                }
+                            },
+                            "filter": [
+                                { "terms": { "kind": kinds } },
+                                { "term": { "site": site_key(query.site_id) } },
+                            ],
+                        }
+                    },
+                    "highlight": {
+                        "pre_tags": [HIGHLIGHT_START.to_string()],
+                        "post_tags": [HIGHLIGHT_END.to_string()],
+                        "fields": { "body": { "fragment_size": 200, "number_of_fragments": 1 } },
+                    },
+                })),
+                &[404],
+            )
+            .await?
+            .unwrap_or_default();
+
+        let hits = body
+            .pointer("/hits/hits")
+            .and_then(Value::as_array)
+            .map(|hits| {
+                hits.iter()
+                    .filter_map(|hit| {
+                        let highlight = hit.pointer("/highlight/body/0").and_then(Value::as_str);
+                        let score = hit
+                            .get("_score")
+                            .and_then(Value::as_f64)
+                            .unwrap_or_default();
+                        hit_from_source(
+                            hit.get("_source")?,
+                            hit.get("_id").and_then(Value::as_str),
+                            score,
+                            highlight,
+                        )
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let total = body
+            .pointer("/hits/total/value")
+            .and_then(Value::as_u64)
+            .unwrap_or_default();
+        Ok(SearchResults::new(query, self.name(), hits, total))
+    }
+
+    async fn suggest(
+        &self,
+        prefix: &str,
+        site_id: Option<Uuid>,
+        limit: u32,
+    ) -> Result<Vec<String>> {
+        let body = self
+            .call(
+                self.request(Method::POST, "/_search").json(&json!({
+                    "size": limit,
+                    "_source": ["title"],
+                    "query": {
+                        "bool": {
+                            "must": { "match_phrase_prefix": { "title": prefix } },
+                            "filter": { "term": { "site": site_key(site_id) } },
+                        }
+                    },
+                })),
+                &[404],
+            )
+            .await?
+            .unwrap_or_default();
+        Ok(titles(body.pointer("/hits/hits"), |hit| {
+            hit.pointer("/_source/title")
+        }))
+    }
+
+    async fn clear(&self) -> Result<()> {
+        self.call(
+            self.request(Method::POST, "/_delete_by_query?conflicts=proceed")
+                .json(&json!({ "query": { "match_all": {} } })),
+            &[404],
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn count(&self) -> Result<u64> {
+        let body = self
+            .call(self.request(Method::GET, "/_count"), &[404])
+            .await?
+            .unwrap_or_default();
+        Ok(body
+            .get("count")
+            .and_then(Value::as_u64)
+            .unwrap_or_default())
+    }
+}
+
+/// Outcome of a full reindex
+#[derive(Debug, Clone, Serialize)]
+pub struct ReindexReport {
+    pub posts: u64,
+    pub pages: u64,
+    pub media: u64,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+}
+
+/// Index counters
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchStats {
+    pub backend: &'static str,
+    pub documents: u64,
+    pub indexed: u64,
+    pub removed: u64,
+    pub failures: u64,
+    pub last_reindex: Option<ReindexReport>,
+}
+
+#[derive(sqlx::FromRow)]
+struct PostRow {
+    id: Uuid,
+    post_type: String,
+    status: String,
+    title: String,
+    excerpt: Option<String>,
+    content: Option<String>,
+    slug: String,
+    published_at: Option<DateTime<Utc>>,
+    updated_at: DateTime<Utc>,
+    deleted_at: Option<DateTime<Utc>>,
+    site_id: Option<Uuid>,
+}
+
+impl PostRow {
+    /// The document to index, or `None` if the post should not be found
+    fn into_document(self) -> Option<SearchDocument> {
+        let kind = match self.post_type.as_str() {
+            "post" => DocumentKind::Post,
+            "page" => DocumentKind::Page,
+            _ => return None,
+        };
+        if self.status != "published" || self.deleted_at.is_some() {
+            return None;
+        }
+        Some(SearchDocument {
+            id: self.id,
+            kind,
+            title: self.title,
+            excerpt: self
+                .excerpt
+                .map(|excerpt| plain_text(&excerpt))
+                .filter(|excerpt| !excerpt.is_empty()),
+            body: plain_text(self.content.as_deref().unwrap_or_default()),
+            slug: Some(self.slug),
+            published_at: self.published_at,
+            updated_at: self.updated_at,
+            site_id: self.site_id,
+        })
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct MediaRow {
+    id: Uuid,
+    original_filename: String,
+    title: Option<String>,
+    alt_text: Option<String>,
+    caption: Option<String>,
+    description: Option<String>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    deleted_at: Option<DateTime<Utc>>,
+    site_id: Option<Uuid>,
+}
+
+impl MediaRow {
+    fn into_document(self) -> Option<SearchDocument> {
+        if self.deleted_at.is_some() {
+            return None;
+        }
+        let body = [
+            &self.alt_text,
+            &self.description,
+            &Some(self.original_filename.clone()),
+        ]
+        .into_iter()
+        .flatten()
+        .map(|text| plain_text(text))
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+        Some(SearchDocument {
+            id: self.id,
+            kind: DocumentKind::Media,
+            title: self
+                .title
+                .filter(|title| !title.trim().is_empty())
+                .unwrap_or(self.original_filename),
+            excerpt: self.caption.map(|caption| plain_text(&caption)),
+            body,
+            slug: None,
+            published_at: Some(self.created_at),
+            updated_at: self.updated_at,
+            site_id: self.site_id,
+        })
+    }
+}
+
+const POST_COLUMNS: &str = "id, post_type, status, title, excerpt, content, slug, \
+                            published_at, updated_at, deleted_at, site_id";
+const MEDIA_COLUMNS: &str = "id, original_filename, title, alt_text, caption, description, \
+                             created_at, updated_at, deleted_at, site_id";
+
+/// Indexes content through the configured backend
+pub struct SearchService {
+    pool: PgPool,
+    backend: Arc<dyn SearchBackend>,
+    /// Rows updated after this were indexed from the change feed
+    synced_at: RwLock<DateTime<Utc>>,
+    last_reindex: RwLock<Option<ReindexReport>>,
+    indexed: AtomicU64,
+    removed: AtomicU64,
+    failures: AtomicU64,
+}
+
+impl SearchService {
+    pub fn new(pool: PgPool, backend: Arc<dyn SearchBackend>) -> Self {
+        Self {
+            pool,
+            backend,
+            synced_at: RwLock::new(Utc::now()),
+            last_reindex: RwLock::new(None),
+            indexed: AtomicU64::new(0),
+            removed: AtomicU64::new(0),
+            failures: AtomicU64::new(0),
+        }
+    }
+
+    /// Create the service with the backend chosen in `[search]`
+    pub fn from_config(pool: PgPool, http: HttpClient, config: &SearchConfig) -> Result<Self> {
+        let url = || {
+            config
+                .url
+                .as_deref()
+                .filter(|url| !url.is_empty())
+                .ok_or_else(|| {
+                    Error::invalid_input("search.url", "A URL is required for this backend")
+                })
+        };
+        let backend: Arc<dyn SearchBackend> = match config.backend {
+            SearchBackendKind::Postgres => {
+                Arc::new(PostgresBackend::new(pool.clone(), &config.language))
+            }
+            SearchBackendKind::Meilisearch => Arc::new(MeilisearchBackend::new(
+                http,
+                url()?,
+                &config.index,
+                config.api_key.clone(),
+            )),
+            SearchBackendKind::Elasticsearch => Arc::new(ElasticsearchBackend::new(
+                http,
+                url()?,
+                &config.index,
+                config.api_key.clone(),
+            )),
+        };
+        Ok(Self::new(pool, backend))
+    }
+
+    pub fn backend(&self) -> &dyn SearchBackend {
+        self.backend.as_ref()
+    }
+
+    pub async fn search(&self, query: SearchQuery) -> Result<SearchResults> {
+        let q = query.q.trim();
+        if q.is_empty() {
+            return Ok(SearchResults::new(
+                &query,
+                self.backend.name(),
+                Vec::new(),
+                0,
+            ));
+        }
+        let query = SearchQuery {
+            q: q.chars().take(MAX_QUERY_LENGTH).collect(),
+            ..query
+        };
+        self.backend.search(&query).await
+    }
+
+    pub async fn suggest(&self, prefix: &str, limit: u32) -> Result<Vec<String>> {
+        let prefix = prefix.trim();
+        if prefix.chars().count() < 2 {
+            return Ok(Vec::new());
+        }
+        let prefix: String = prefix.chars().take(MAX_QUERY_LENGTH).collect();
+        self.backend
+            .suggest(&prefix, current_site(), limit.clamp(1, 20))
+            .await
+    }
+
+    pub async fn stats(&self) -> Result<SearchStats> {
+        Ok(SearchStats {
+            backend: self.backend.name(),
+            documents: self.backend.count().await?,
+            indexed: self.indexed.load(Ordering::Relaxed),
+            removed: self.removed.load(Ordering::Relaxed),
+            failures: self.failures.load(Ordering::Relaxed),
+            last_reindex: self.last_reindex.read().clone(),
+        })
+    }
+
+    /// Index or remove the row behind a change
+    pub async fn handle_change(&self, change: &RowChange) -> Result<()> {
+        let Some(id) = change.id else {
+            return Ok(());
+        };
+        match change.entity() {
+            "post" => {
+                let rows = self.load_posts("id = $1", id).await?;
+                self.apply(id, rows.into_iter().find_map(PostRow::into_document))
+                    .await
+            }
+            "media" => {
+                let rows = self.load_media("id = $1", id).await?;
+                self.apply(id, rows.into_iter().find_map(MediaRow::into_document))
+                    .await
+            }
+            _ => Ok(()),
+        }
+    }
+
+    async fn apply(&self, id: Uuid, document: Option<SearchDocument>) -> Result<()> {
+        match document {
+            Some(document) => self.index(vec![document]).await,
+            None => {
+                self.backend.remove(&[id]).await?;
+                self.removed.fetch_add(1, Ordering::Relaxed);
+                Ok(())
+            }
+        }
+    }
+
+    async fn index(&self, documents: Vec<SearchDocument>) -> Result<()> {
+        self.backend.index(&documents).await?;
+        self.indexed
+            .fetch_add(documents.len() as u64, Ordering::Relaxed);
+        Ok(())
+    }
+
+    async fn load_posts(&self, filter: &str, value: Uuid) -> Result<Vec<PostRow>> {
+        sqlx::query_as(&format!(
+            "SELECT {} FROM posts WHERE {} ORDER BY id LIMIT {}",
+            POST_COLUMNS, filter, REINDEX_BATCH
+        ))
+        .bind(value)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load posts for search", e))
+    }
+
+    async fn load_media(&self, filter: &str, value: Uuid) -> Result<Vec<MediaRow>> {
+        sqlx::query_as(&format!(
+            "SELECT {} FROM media WHERE {} ORDER BY id LIMIT {}",
+            MEDIA_COLUMNS, filter, REINDEX_BATCH
+        ))
+        .bind(value)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load media for search", e))
+    }
+
+    /// Clear the index and index every published post, page and media item
+    pub async fn reindex(&self) -> Result<ReindexReport> {
+        let started_at = Utc::now();
+        self.backend.prepare().await?;
+        self.backend.clear().await?;
+
+        let mut report = ReindexReport {
+            posts: 0,
+            pages: 0,
+            media: 0,
+            started_at,
+            finished_at: started_at,
+        };
+
+        let mut after = Uuid::nil();
+        loop {
+            let rows = self
+                .load_posts(
+                    "id > $1 AND post_type IN ('post', 'page', 'episode')",
+                    after,
+                )
+                .await?;
+            let Some(last) = rows.last() else { break };
+            after = last.id;
+            let documents: Vec<_> = rows
+                .into_iter()
+                .filter_map(PostRow::into_document)
+                .collect();
+            for document in &documents {
+                match document.kind {
+                    DocumentKind::Page => report.pages += 1,
+                    _ => report.posts += 1,
+                }
+            }
+            self.index(documents).await?;
+        }
+
+        let mut after = Uuid::nil();
+        loop {
+            let rows = self.load_media("id > $1", after).await?;
+            let Some(last) = rows.last() else { break };
+            after = last.id;
+            let documents: Vec<_> = rows
+                .into_iter()
+                .filter_map(MediaRow::into_document)
+                .collect();
+            report.media += documents.len() as u64;
+            self.index(documents).await?;
+        }
+
+        report.finished_at = Utc::now();
+        *self.synced_at.write() = started_at;
+        *self.last_reindex.write() = Some(report.clone());
+        tracing::info!(
+            posts = report.posts,
+            pages = report.pages,
+            media = report.media,
+            "Search index rebuilt"
+        );
+        Ok(report)
+    }
+
+    /// Index again rows updated since the last sync, after the change feed
+    /// may have missed some
+    pub async fn catch_up(&self) -> Result<()> {
+        let since = *self.synced_at.read();
+        let started_at = Utc::now();
+        // Commits can land slightly after their updated_at
+        let since = since - chrono::Duration::minutes(1);
+
+        let mut after = Uuid::nil();
+        loop {
+            let rows = self.load_posts_since(since, after).await?;
+            let Some(last) = rows.last() else { break };
+            after = last.id;
+            for row in rows {
+                let id = row.id;
+                self.apply(id, row.into_document()).await?;
+            }
+        }
+
+        let mut after = Uuid::nil();
+        loop {
+            let rows = self.load_media_since(since, after).await?;
+            let Some(last) = rows.last() else { break };
+            after = last.id;
+            for row in rows {
+                let id = row.id;
+                self.apply(id, row.into_document()).await?;
+            }
+        }
+
+        *self.synced_at.write() = started_at;
+        Ok(())
+    }
+
+    async fn load_posts_since(&self, since: DateTime<Utc>, after: Uuid) -> Result<Vec<PostRow>> {
+        sqlx::query_as(&format!(
+            "SELECT {} FROM posts WHERE updated_at >= $1 AND id > $2 ORDER BY id LIMIT {}",
+            POST_COLUMNS, REINDEX_BATCH
+        ))
+        .bind(since)
+        .bind(after)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load posts for search", e))
+    }
+
+    async fn load_media_since(&self, since: DateTime<Utc>, after: Uuid) -> Result<Vec<MediaRow>> {
+        sqlx::query_as(&format!(
+            "SELECT {} FROM media WHERE updated_at >= $1 AND id > $2 ORDER BY id LIMIT {}",
+            MEDIA_COLUMNS, REINDEX_BATCH
+        ))
+        .bind(since)
+        .bind(after)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load media for search", e))
+    }
+
+    /// Keep the index in step with the change feed
+    pub fn subscribe(self: &Arc<Self>, bus: &EventBus) {
+        let mut event_types: Vec<EventType> = ["post", "media"]
+            .iter()
+            .flat_map(|entity| {
+                ["created", "updated", "deleted"]
+                    .iter()
+                    .map(move |op| EventType::new(format!("change.{}.{}", entity, op)))
+            })
+            .collect();
+        event_types.push(EventType::new(RESYNC_EVENT));
+
+        let search = Arc::downgrade(self);
+        bus.subscribe(Subscriber::new(
+            "search_indexer",
+            SubscriberConfig::new(event_types).async_handler(),
+            move |event| {
+                let search = search.clone();
+                async move {
+                    let Some(search) = search.upgrade() else {
+                        return Ok(());
+                    };
+                    let result = if event.event_type == RESYNC_EVENT {
+                        search.catch_up().await
+                    } else {
+                        match serde_json::from_value::<RowChange>(event.payload.clone()) {
+                            Ok(change) => search.handle_change(&change).await,
+                            Err(_) => Ok(()),
+                        }
+                    };
+                    if let Err(e) = &result {
+                        search.failures.fetch_add(1, Ordering::Relaxed);
+                        tracing::warn!(event_type = %event.event_type, "Search indexing failed: {}", e);
+                    }
+                    result
+                }
+            },
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kinds_and_paging() {
+        assert_eq!(
+            DocumentKind::parse_list("post, pages,,media").unwrap(),
+            vec![DocumentKind::Post, DocumentKind::Page, DocumentKind::Media]
+        );
+        assert!(DocumentKind::parse_list("post,comment").is_err());
+
+        let query = SearchQuery::new("rust").page(0, 500);
+        assert_eq!((query.page, query.per_page, query.offset()), (1, 100, 0));
+        assert_eq!(query.effective_kinds().len(), 3);
+
+        let query = SearchQuery::new("rust").page(3, 10);
+        assert_eq!(query.offset(), 20);
+        let results = SearchResults::new(&query, "postgres", Vec::new(), 21);
+        assert_eq!(results.total_pages, 3);
+    }
+
+    #[test]
+    fn test_highlight_is_escaped() {
+        let fragment = format!("a <b> {}rust{} & more", HIGHLIGHT_START, HIGHLIGHT_END);
+        assert_eq!(
+            render_highlight(&fragment),
+            "a &lt;b&gt; <mark>rust</mark> &amp; more"
+        );
+    }
+
+    #[test]
+    fn test_documents_from_rows() {
+        let now = Utc::now();
+        let post = PostRow {
+            id: Uuid::new_v4(),
+            post_type: "page".to_string(),
+            status: "published".to_string(),
+            title: "About".to_string(),
+            excerpt: Some(String::new()),
+            content: Some("<p>Hello   <strong>world</strong></p>".to_string()),
+            slug: "about".to_string(),
+            published_at: Some(now),
+            updated_at: now,
+            deleted_at: None,
+            site_id: None,
+        };
+        let document = post.into_document().unwrap();
+        assert_eq!(document.kind, DocumentKind::Page);
+        assert_eq!(document.excerpt, None);
+        assert!(document.body.contains("Hello") && !document.body.contains('<'));
+
+        let draft = PostRow {
+            id: Uuid::new_v4(),
+            post_type: "post".to_string(),
+            status: "draft".to_string(),
+            title: "Draft".to_string(),
+            excerpt: None,
+            content: None,
+            slug: "draft".to_string(),
+            published_at: None,
+            updated_at: now,
+            deleted_at: None,
+            site_id: None,
+        };
+        assert!(draft.into_document().is_none());
+
+        let media = MediaRow {
+            id: Uuid::new_v4(),
+            original_filename: "sunset.jpg".to_string(),
+            title: None,
+            alt_text: Some("Sunset over the bay".to_string()),
+            caption: None,
+            description: None,
+            created_at: now,
+            updated_at: now,
+            deleted_at: None,
+            site_id: None,
+        };
+        let document = media.into_document().unwrap();
+        assert_eq!(document.title, "sunset.jpg");
+        assert_eq!(document.body, "Sunset over the bay sunset.jpg");
+    }
+
+    #[test]
+    fn test_search_hit_from_remote_document() {
+        let id = Uuid::new_v4();
+        let source = json!({
+            "kind": "media",
+            "title": "Logo",
+            "published_at": 1_700_000_000,
+        });
+        let hit = hit_from_source(&source, Some(&id.to_string()), 1.5, None).unwrap();
+        assert_eq!(hit.id, id);
+        assert_eq!(hit.kind, DocumentKind::Media);
+        assert!(hit.published_at.is_some());
+        assert!(hit_from_source(&json!({ "kind": "post" }), None, 0.0, None).is_none());
+    }
+}
@@ -0,0 +1,735 @@
+//! Four-Eyes Approval of Sensitive Settings
+//!
+//! In regulated environments no single administrator should be able to
+//! change security, authentication or billing settings alone. With
+//! approval mode on, writes touching a sensitive setting are not applied
+//! but turned into a pending change request. Another administrator
+//! reviews the diff and approves it, which applies every change of the
+//! request at once, or rejects it. Requesters can cancel their own
+//! requests, and requests nobody decided on expire.
+//!
+//! Approval mode is configured by the `settings_approval` option, which
+//! always counts as sensitive itself, so switching the mode off needs a
+//! second administrator too. Each change records the setting's version
+//! when it was requested; if the setting changed since, approving fails
+//! and the request is marked superseded rather than overwriting the newer
+//! value. Everything that happens to a request is kept in
+//! `setting_change_events`.
+
+use chrono::{DateTime, Duration, Utc};
+use rustpress_api::services::settings_service::{SettingResponse, SettingUpdate, SettingsService};
+use rustpress_core::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::types::Json;
+use sqlx::{FromRow, PgPool};
+use std::fmt;
+use std::str::FromStr;
+use uuid::Uuid;
+
+/// Option configuring approval mode
+pub const APPROVAL_SETTING_KEY: &str = "settings_approval";
+
+/// Longest a request may stay pending
+pub const MAX_EXPIRY_HOURS: u32 = 24 * 30;
+
+const REQUEST_COLUMNS: &str = "id, status, changes, reason, requested_by, reviewed_by, \
+     review_note, created_at, expires_at, decided_at";
+
+/// Which settings need a second administrator's approval
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ApprovalConfig {
+    pub enabled: bool,
+    /// Setting groups whose every setting is sensitive
+    pub sensitive_groups: Vec<String>,
+    /// Further sensitive settings, by key
+    pub sensitive_keys: Vec<String>,
+    /// Hours after which undecided requests expire
+    pub expiry_hours: u32,
+}
+
+impl Default for ApprovalConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            sensitive_groups: vec![
+                "security".to_string(),
+                "auth".to_string(),
+                "billing".to_string(),
+            ],
+            sensitive_keys: Vec::new(),
+            expiry_hours: 72,
+        }
+    }
+}
+
+impl ApprovalConfig {
+    /// Whether changing the setting `key` of `group` needs approval
+    pub fn requires_approval(&self, key: &str, group: Option<&str>) -> bool {
+        self.enabled
+            && (key == APPROVAL_SETTING_KEY
+                || self.sensitive_keys.iter().any(|k| k == key)
+                || group.is_some_and(|group| self.sensitive_groups.iter().any(|g| g == group)))
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.expiry_hours == 0 || self.expiry_hours > MAX_EXPIRY_HOURS {
+            return Err(Error::invalid_input(
+                "expiry_hours",
+                format!("Must be between 1 and {}", MAX_EXPIRY_HOURS),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Where a change request stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeRequestStatus {
+    Pending,
+    Applied,
+    Rejected,
+    Cancelled,
+    Expired,
+    /// A setting changed after the request was made
+    Superseded,
+}
+
+impl ChangeRequestStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Applied => "applied",
+            Self::Rejected => "rejected",
+            Self::Cancelled => "cancelled",
+            Self::Expired => "expired",
+            Self::Superseded => "superseded",
+        }
+    }
+}
+
+impl fmt::Display for ChangeRequestStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ChangeRequestStatus {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "pending" => Ok(Self::Pending),
+            "applied" => Ok(Self::Applied),
+            "rejected" => Ok(Self::Rejected),
+            "cancelled" => Ok(Self::Cancelled),
+            "expired" => Ok(Self::Expired),
+            "superseded" => Ok(Self::Superseded),
+            _ => Err(Error::invalid_input(
+                "status",
+                format!(
+                    "Unknown status '{}'; expected one of: pending, applied, rejected, \
+                     cancelled, expired, superseded",
+                    s
+                ),
+            )),
+        }
+    }
+}
+
+/// One setting a request changes, with its diff preview
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SettingDiff {
+    pub key: String,
+    /// `None` for settings that don't exist yet
+    pub group: Option<String>,
+    pub old_value: Value,
+    pub new_value: Value,
+    /// Version of the setting when requested; `None` if it didn't exist
+    pub version: Option<i64>,
+}
+
+impl SettingDiff {
+    fn new(update: SettingUpdate, current: Option<SettingResponse>) -> Self {
+        match current {
+            Some(current) => Self {
+                key: update.key,
+                group: Some(current.group),
+                old_value: current.value,
+                new_value: update.value,
+                version: Some(update.version.unwrap_or(current.version)),
+            },
+            None => Self {
+                key: update.key,
+                group: None,
+                old_value: Value::Null,
+                new_value: update.value,
+                version: None,
+            },
+        }
+    }
+
+    /// Whether the change alters the stored value
+    pub fn is_change(&self) -> bool {
+        self.old_value != self.new_value
+    }
+}
+
+/// A change to sensitive settings waiting for, or decided by, a second
+/// administrator
+#[derive(Debug, Clone, Serialize)]
+pub struct ChangeRequest {
+    pub id: Uuid,
+    pub status: ChangeRequestStatus,
+    pub changes: Vec<SettingDiff>,
+    pub reason: Option<String>,
+    pub requested_by: Uuid,
+    pub reviewed_by: Option<Uuid>,
+    pub review_note: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub decided_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, FromRow)]
+struct ChangeRequestRow {
+    id: Uuid,
+    status: String,
+    changes: Json<Vec<SettingDiff>>,
+    reason: Option<String>,
+    requested_by: Uuid,
+    reviewed_by: Option<Uuid>,
+    review_note: Option<String>,
+    created_at: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+    decided_at: Option<DateTime<Utc>>,
+}
+
+impl TryFrom<ChangeRequestRow> for ChangeRequest {
+    type Error = Error;
+
+    fn try_from(row: ChangeRequestRow) -> Result<Self> {
+        Ok(Self {
+            id: row.id,
+            status: row.status.parse()?,
+            changes: row.changes.0,
+            reason: row.reason,
+            requested_by: row.requested_by,
+            reviewed_by: row.reviewed_by,
+            review_note: row.review_note,
+            created_at: row.created_at,
+            expires_at: row.expires_at,
+            decided_at: row.decided_at,
+        })
+    }
+}
+
+/// Something that happened to a change request
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct ChangeRequestEvent {
+    pub id: Uuid,
+    pub request_id: Uuid,
+    /// requested, approved, applied, rejected, cancelled, expired or
+    /// superseded
+    pub action: String,
+    /// `None` when the system acted
+    pub actor_id: Option<Uuid>,
+    pub note: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Routes writes to sensitive settings through change requests
+pub struct SettingApprovalService {
+    pool: PgPool,
+}
+
+impl SettingApprovalService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    fn settings(&self) -> SettingsService {
+        SettingsService::new(self.pool.clone())
+    }
+
+    /// Current approval configuration
+    pub async fn config(&self) -> Result<ApprovalConfig> {
+        match self.settings().get_value(APPROVAL_SETTING_KEY).await? {
+            Some(value) => serde_json::from_value(value).map_err(|e| {
+                Error::deserialization(format!("Invalid {} option: {}", APPROVAL_SETTING_KEY, e))
+            }),
+            None => Ok(ApprovalConfig::default()),
+        }
+    }
+
+    /// Check a settings write before it is made. Returns `None` when it
+    /// can be saved right away, or the diff of every update when one of
+    /// them needs approval, in which case all of them go into one
+    /// [`request`](Self::request)
+    pub async fn review(&self, updates: &[SettingUpdate]) -> Result<Option<Vec<SettingDiff>>> {
+        if let Some(update) = updates.iter().find(|u| u.key == APPROVAL_SETTING_KEY) {
+            let config: ApprovalConfig = serde_json::from_value(update.value.clone())
+                .map_err(|e| Error::invalid_input(APPROVAL_SETTING_KEY, e.to_string()))?;
+            config.validate()?;
+        }
+
+        let config = self.config().await?;
+        if !config.enabled {
+            return Ok(None);
+        }
+
+        let settings = self.settings();
+        let mut diffs = Vec::with_capacity(updates.len());
+        let mut sensitive = false;
+        for update in updates {
+            let current = settings.get(&update.key).await?;
+            let diff = SettingDiff::new(update.clone(), current);
+            sensitive |= config.requires_approval(&diff.key, diff.group.as_deref());
+            diffs.push(diff);
+        }
+        Ok(sensitive.then_some(diffs))
+    }
+
+    /// Put changes up for approval by another administrator
+    pub async fn request(
+        &self,
+        changes: Vec<SettingDiff>,
+        requested_by: Uuid,
+        reason: Option<String>,
+    ) -> Result<ChangeRequest> {
+        if !changes.iter().any(SettingDiff::is_change) {
+            return Err(Error::validation("The request doesn't change any setting"));
+        }
+
+        let config = self.config().await?;
+        let expires_at = Utc::now() + Duration::hours(i64::from(config.expiry_hours));
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| Error::database_with_source("Failed to begin transaction", e))?;
+
+        let row: ChangeRequestRow = sqlx::query_as(&format!(
+            "INSERT INTO setting_change_requests (status, changes, reason, requested_by, expires_at) \
+             VALUES ('pending', $1, $2, $3, $4) RETURNING {}",
+            REQUEST_COLUMNS
+        ))
+        .bind(Json(&changes))
+        .bind(&reason)
+        .bind(requested_by)
+        .bind(expires_at)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to create setting change request", e))?;
+
+        record_event(
+            &mut *tx,
+            row.id,
+            "requested",
+            Some(requested_by),
+            reason.as_deref(),
+        )
+        .await?;
+        tx.commit()
+            .await
+            .map_err(|e| Error::database_with_source("Failed to commit change request", e))?;
+
+        let request = ChangeRequest::try_from(row)?;
+        tracing::info!(
+            request_id = %request.id,
+            requested_by = %requested_by,
+            settings = ?request.changes.iter().map(|c| c.key.as_str()).collect::<Vec<_>>(),
+            "Sensitive settings change waiting for approval"
+        );
+        Ok(request)
+    }
+
+    /// Change requests, newest first
+    pub async fn list(&self, status: Option<ChangeRequestStatus>) -> Result<Vec<ChangeRequest>> {
+        self.expire_stale().await?;
+
+        let rows: Vec<ChangeRequestRow> = sqlx::query_as(&format!(
+            "SELECT {} FROM setting_change_requests \
+             WHERE $1::text IS NULL OR status = $1 ORDER BY created_at DESC LIMIT 200",
+            REQUEST_COLUMNS
+        ))
+        .bind(status.map(|s| s.as_str()))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to list setting change requests", e))?;
+
+        rows.into_iter().map(ChangeRequest::try_from).collect()
+    }
+
+    /// A single change request
+    pub async fn get(&self, id: Uuid) -> Result<ChangeRequest> {
+        self.expire_stale().await?;
+        self.find(id).await
+    }
+
+    async fn find(&self, id: Uuid) -> Result<ChangeRequest> {
+        let row: Option<ChangeRequestRow> = sqlx::query_as(&format!(
+            "SELECT {} FROM setting_change_requests WHERE id = $1",
+            REQUEST_COLUMNS
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load setting change request", e))?;
+
+        row.ok_or_else(|| Error::not_found("SettingChangeRequest", id.to_string()))?
+            .try_into()
+    }
+
+    /// Audit trail of a change request, oldest first
+    pub async fn history(&self, id: Uuid) -> Result<Vec<ChangeRequestEvent>> {
+        sqlx::query_as(
+            "SELECT id, request_id, action, actor_id, note, created_at \
+             FROM setting_change_events WHERE request_id = $1 ORDER BY created_at, id",
+        )
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load change request history", e))
+    }
+
+    /// Approve a pending request and apply its changes. The reviewer must
+    /// not be the requester
+    pub async fn approve(
+        &self,
+        id: Uuid,
+        reviewer: Uuid,
+        note: Option<String>,
+    ) -> Result<(ChangeRequest, Vec<SettingResponse>)> {
+        self.expire_stale().await?;
+        let request = self.find(id).await?;
+        check_decidable(&request, reviewer)?;
+
+        // Claim the request first so concurrent reviewers can't both apply it
+        let claimed = self
+            .decide(
+                id,
+                ChangeRequestStatus::Applied,
+                reviewer,
+                "approved",
+                note.as_deref(),
+            )
+            .await?;
+
+        match self.apply(&claimed.changes).await {
+            Ok(applied) => {
+                record_event(&self.pool, id, "applied", None, None).await?;
+                tracing::info!(request_id = %id, reviewer = %reviewer, "Sensitive settings change approved");
+                Ok((claimed, applied))
+            }
+            Err(e @ (Error::VersionConflict { .. } | Error::NotFound { .. })) => {
+                self.supersede(id, &e).await?;
+                Err(e)
+            }
+            Err(e) => {
+                // Put the request back so it can be approved once the
+                // failure is resolved
+                sqlx::query(
+                    "UPDATE setting_change_requests \
+                     SET status = 'pending', reviewed_by = NULL, review_note = NULL, decided_at = NULL \
+                     WHERE id = $1",
+                )
+                .bind(id)
+                .execute(&self.pool)
+                .await
+                .map_err(|e| Error::database_with_source("Failed to reopen change request", e))?;
+                record_event(&self.pool, id, "reopened", None, Some(&e.to_string())).await?;
+                Err(e)
+            }
+        }
+    }
+
+    /// Reject a pending request. The reviewer must not be the requester,
+    /// who cancels instead
+    pub async fn reject(
+        &self,
+        id: Uuid,
+        reviewer: Uuid,
+        note: Option<String>,
+    ) -> Result<ChangeRequest> {
+        self.expire_stale().await?;
+        let request = self.find(id).await?;
+        check_decidable(&request, reviewer)?;
+        self.decide(
+            id,
+            ChangeRequestStatus::Rejected,
+            reviewer,
+            "rejected",
+            note.as_deref(),
+        )
+        .await
+    }
+
+    /// Withdraw a pending request; only its requester can
+    pub async fn cancel(&self, id: Uuid, user: Uuid) -> Result<ChangeRequest> {
+        self.expire_stale().await?;
+        let request = self.find(id).await?;
+        if request.requested_by != user {
+            return Err(Error::forbidden(
+                "Only the requester can cancel a change request",
+            ));
+        }
+        check_pending(&request)?;
+        self.decide(id, ChangeRequestStatus::Cancelled, user, "cancelled", None)
+            .await
+    }
+
+    /// Expire every pending request past its expiry. Returns how many
+    pub async fn expire_stale(&self) -> Result<u64> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| Error::database_with_source("Failed to begin transaction", e))?;
+
+        let expired: Vec<Uuid> = sqlx::query_scalar(
+            "UPDATE setting_change_requests SET status = 'expired', decided_at = NOW() \
+             WHERE status = 'pending' AND expires_at <= NOW() RETURNING id",
+        )
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to expire change requests", e))?;
+
+        for id in &expired {
+            record_event(&mut *tx, *id, "expired", None, None).await?;
+        }
+        tx.commit()
+            .await
+            .map_err(|e| Error::database_with_source("Failed to commit expired requests", e))?;
+
+        if !expired.is_empty() {
+            tracing::info!(
+                count = expired.len(),
+                "Expired stale setting change requests"
+            );
+        }
+        Ok(expired.len() as u64)
+    }
+
+    /// Move a pending request to `status`, recording the decision
+    async fn decide(
+        &self,
+        id: Uuid,
+        status: ChangeRequestStatus,
+        actor: Uuid,
+        action: &str,
+        note: Option<&str>,
+    ) -> Result<ChangeRequest> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| Error::database_with_source("Failed to begin transaction", e))?;
+
+        let row: Option<ChangeRequestRow> = sqlx::query_as(&format!(
+            "UPDATE setting_change_requests \
+             SET status = $2, reviewed_by = $3, review_note = $4, decided_at = NOW() \
+             WHERE id = $1 AND status = 'pending' RETURNING {}",
+            REQUEST_COLUMNS
+        ))
+        .bind(id)
+        .bind(status.as_str())
+        .bind(actor)
+        .bind(note)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to update change request", e))?;
+
+        let Some(row) = row else {
+            return Err(Error::validation(
+                "The change request was decided by someone else",
+            ));
+        };
+        record_event(&mut *tx, id, action, Some(actor), note).await?;
+        tx.commit()
+            .await
+            .map_err(|e| Error::database_with_source("Failed to commit change request", e))?;
+
+        row.try_into()
+    }
+
+    async fn supersede(&self, id: Uuid, conflict: &Error) -> Result<()> {
+        sqlx::query("UPDATE setting_change_requests SET status = 'superseded' WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to update change request", e))?;
+        record_event(
+            &self.pool,
+            id,
+            "superseded",
+            None,
+            Some(&conflict.to_string()),
+        )
+        .await
+    }
+
+    /// Save the changes, failing them all if a setting changed since the
+    /// diff was taken
+    async fn apply(&self, changes: &[SettingDiff]) -> Result<Vec<SettingResponse>> {
+        let settings = self.settings();
+        let (existing, new): (Vec<&SettingDiff>, Vec<&SettingDiff>) =
+            changes.iter().partition(|c| c.version.is_some());
+
+        let mut applied = settings
+            .batch_update(
+                existing
+                    .into_iter()
+                    .map(|c| SettingUpdate {
+                        key: c.key.clone(),
+                        value: c.new_value.clone(),
+                        version: c.version,
+                    })
+                    .collect(),
+            )
+            .await?;
+        for change in new {
+            applied.push(
+                settings
+                    .update(&change.key, change.new_value.clone())
+                    .await?,
+            );
+        }
+        Ok(applied)
+    }
+}
+
+fn check_pending(request: &ChangeRequest) -> Result<()> {
+    if request.status != ChangeRequestStatus::Pending {
+        return Err(Error::validation(format!(
+            "The change request is {}",
+            request.status
+        )));
+    }
+    Ok(())
+}
+
+/// A second administrator must decide on a pending request
+fn check_decidable(request: &ChangeRequest, reviewer: Uuid) -> Result<()> {
+    check_pending(request)?;
+    if request.requested_by == reviewer {
+        return Err(Error::forbidden(
+            "A change request must be reviewed by another administrator",
+        ));
+    }
+    Ok(())
+}
+
+async fn record_event<'e>(
+    executor: impl sqlx::PgExecutor<'e>,
+    request_id: Uuid,
+    action: &str,
+    actor_id: Option<Uuid>,
+    note: Option<&str>,
+) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO setting_change_events (request_id, action, actor_id, note) \
+         VALUES ($1, $2, $3, $4)",
+    )
+    .bind(request_id)
+    .bind(action)
+    .bind(actor_id)
+    .bind(note)
+    .execute(executor)
+    .await
+    .map_err(|e| Error::database_with_source("Failed to record change request event", e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn request(requested_by: Uuid, status: ChangeRequestStatus) -> ChangeRequest {
+        ChangeRequest {
+            id: Uuid::new_v4(),
+            status,
+            changes: Vec::new(),
+            reason: None,
+            requested_by,
+            reviewed_by: None,
+            review_note: None,
+            created_at: Utc::now(),
+            expires_at: Utc::now() + Duration::hours(1),
+            decided_at: None,
+        }
+    }
+
+    #[test]
+    fn test_requires_approval() {
+        let mut config = ApprovalConfig::default();
+        assert!(!config.requires_approval("two_factor_required", Some("security")));
+
+        config.enabled = true;
+        config.sensitive_keys = vec!["smtp_password".to_string()];
+        assert!(config.requires_approval("two_factor_required", Some("security")));
+        assert!(config.requires_approval("smtp_password", Some("general")));
+        assert!(config.requires_approval(APPROVAL_SETTING_KEY, None));
+        assert!(!config.requires_approval("site_title", Some("general")));
+        assert!(!config.requires_approval("new_key", None));
+
+        let stored: ApprovalConfig = serde_json::from_value(json!({"enabled": true})).unwrap();
+        assert_eq!(stored.sensitive_groups, ["security", "auth", "billing"]);
+    }
+
+    #[test]
+    fn test_diff_records_version_read() {
+        let current = SettingResponse {
+            key: "session_lifetime".to_string(),
+            value: json!(3600),
+            group: "auth".to_string(),
+            display_name: None,
+            description: None,
+            value_type: None,
+            is_system: true,
+            version: 4,
+        };
+        let update = |value, version| SettingUpdate {
+            key: "session_lifetime".to_string(),
+            value,
+            version,
+        };
+
+        let diff = SettingDiff::new(update(json!(600), None), Some(current.clone()));
+        assert_eq!(diff.old_value, json!(3600));
+        assert_eq!(diff.version, Some(4));
+        assert!(diff.is_change());
+
+        // A version the client read earlier wins over the current one
+        let diff = SettingDiff::new(update(json!(3600), Some(3)), Some(current));
+        assert_eq!(diff.version, Some(3));
+        assert!(!diff.is_change());
+
+        let diff = SettingDiff::new(update(json!(1), None), None);
+        assert_eq!((diff.group, diff.version), (None, None));
+    }
+
+    #[test]
+    fn test_second_administrator_decides() {
+        let requester = Uuid::new_v4();
+        let pending = request(requester, ChangeRequestStatus::Pending);
+
+        assert!(check_decidable(&pending, Uuid::new_v4()).is_ok());
+        assert_eq!(
+            check_decidable(&pending, requester)
+                .unwrap_err()
+                .error_code(),
+            "FORBIDDEN"
+        );
+
+        let expired = request(requester, ChangeRequestStatus::Expired);
+        assert!(check_decidable(&expired, Uuid::new_v4()).is_err());
+        assert_eq!(
+            "superseded".parse::<ChangeRequestStatus>().unwrap(),
+            ChangeRequestStatus::Superseded
+        );
+    }
+}
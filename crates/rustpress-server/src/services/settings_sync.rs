@@ -0,0 +1,265 @@
+//! Cross-node invalidation of cached settings.
+//!
+//! Services keep their settings in memory after the first read. Triggers
+//! on the `settings`, `options` and `extension_allowlists` tables announce
+//! every committed change on [`SETTINGS_CHANNEL`]; each node listens and
+//! drops the affected copies so the next read loads the new value. The
+//! node that made the change hears it too and simply reloads once.
+
+use futures::future::{join_all, BoxFuture};
+use rustpress_core::error::{Error, Result};
+use sqlx::postgres::PgListener;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+/// Postgres notification channel settings changes are announced on
+pub const SETTINGS_CHANNEL: &str = "rustpress_settings";
+
+/// Longest wait between listener reconnection attempts
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// Changes kept for subscribers that fall behind
+const CHANGE_BUFFER: usize = 256;
+
+/// A stored setting that changed
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SettingsChange {
+    /// A row of the `settings` table, by key
+    Setting(String),
+    /// A site option, by name
+    SiteOption(String),
+    /// Any theme or plugin allowlist
+    Allowlists,
+    /// Anything may have changed, e.g. after the listener reconnected
+    All,
+}
+
+impl SettingsChange {
+    pub fn setting(key: impl Into<String>) -> Self {
+        Self::Setting(key.into())
+    }
+
+    pub fn site_option(name: impl Into<String>) -> Self {
+        Self::SiteOption(name.into())
+    }
+
+    /// Parse a notification payload sent by the triggers
+    pub fn parse(payload: &str) -> Option<Self> {
+        match payload {
+            "allowlists" => Some(Self::Allowlists),
+            "*" => Some(Self::All),
+            _ => match payload.split_once(':')? {
+                ("setting", key) if !key.is_empty() => Some(Self::setting(key)),
+                ("option", name) if !name.is_empty() => Some(Self::site_option(name)),
+                _ => None,
+            },
+        }
+    }
+
+    /// Notification payload for the change
+    pub fn payload(&self) -> String {
+        match self {
+            Self::Setting(key) => format!("setting:{}", key),
+            Self::SiteOption(name) => format!("option:{}", name),
+            Self::Allowlists => "allowlists".to_string(),
+            Self::All => "*".to_string(),
+        }
+    }
+}
+
+type Invalidate = Arc<dyn Fn() -> BoxFuture<'static, ()> + Send + Sync>;
+
+/// Drops cached settings when any node changes them
+pub struct SettingsSync {
+    pool: PgPool,
+    handlers: parking_lot::RwLock<HashMap<SettingsChange, Vec<Invalidate>>>,
+    changes: broadcast::Sender<SettingsChange>,
+}
+
+impl SettingsSync {
+    pub fn new(pool: PgPool) -> Self {
+        let (changes, _) = broadcast::channel(CHANGE_BUFFER);
+        Self {
+            pool,
+            handlers: parking_lot::RwLock::new(HashMap::new()),
+            changes,
+        }
+    }
+
+    /// Run `invalidate` on `target` whenever `change` is announced, and
+    /// after the listener reconnects
+    pub fn watch<S, F, Fut>(&self, change: SettingsChange, target: Arc<S>, invalidate: F)
+    where
+        S: Send + Sync + 'static,
+        F: Fn(Arc<S>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let handler: Invalidate = Arc::new(move || Box::pin(invalidate(target.clone())));
+        self.handlers
+            .write()
+            .entry(change)
+            .or_default()
+            .push(handler);
+    }
+
+    /// Every change this node hears about, for plugins and other code that
+    /// keeps its own copies
+    pub fn subscribe(&self) -> broadcast::Receiver<SettingsChange> {
+        self.changes.subscribe()
+    }
+
+    /// Announce a change the triggers cannot see, e.g. `All` after a
+    /// backup was restored
+    pub async fn notify(&self, change: &SettingsChange) -> Result<()> {
+        sqlx::query("SELECT pg_notify($1, $2)")
+            .bind(SETTINGS_CHANNEL)
+            .bind(change.payload())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to announce settings change", e))?;
+        Ok(())
+    }
+
+    /// Drop the copies affected by `change` on this node
+    pub async fn apply(&self, change: SettingsChange) {
+        let handlers: Vec<Invalidate> = {
+            let handlers = self.handlers.read();
+            match change {
+                SettingsChange::All => handlers.values().flatten().cloned().collect(),
+                _ => handlers.get(&change).cloned().unwrap_or_default(),
+            }
+        };
+        join_all(handlers.iter().map(|invalidate| invalidate())).await;
+
+        tracing::debug!(change = %change.payload(), handlers = handlers.len(), "Settings changed");
+        // Nobody may be subscribed
+        let _ = self.changes.send(change);
+    }
+
+    /// Apply changes announced by any node
+    ///
+    /// The listener reconnects with backoff and stops once the service is
+    /// dropped. Every cached copy is dropped after a reconnect, since
+    /// notifications sent while disconnected are lost.
+    pub fn spawn_listener(self: &Arc<Self>) -> tokio::task::JoinHandle<()> {
+        let sync = Arc::downgrade(self);
+        let pool = self.pool.clone();
+
+        tokio::spawn(async move {
+            let mut delay = Duration::from_secs(1);
+            let mut resync = false;
+            loop {
+                match listen(&sync, &pool, resync).await {
+                    Ok(()) => return,
+                    Err(e) => tracing::warn!("Settings change listener disconnected: {}", e),
+                }
+                tokio::time::sleep(delay).await;
+                delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+                resync = true;
+            }
+        })
+    }
+}
+
+/// Listen on the settings channel and apply notifications until the
+/// connection drops (`Err`) or the service is gone (`Ok`)
+async fn listen(sync: &Weak<SettingsSync>, pool: &PgPool, resync: bool) -> Result<()> {
+    let listen_error = |e| Error::database_with_source("Settings change listener failed", e);
+    let mut listener = PgListener::connect_with(pool).await.map_err(listen_error)?;
+    listener
+        .listen(SETTINGS_CHANNEL)
+        .await
+        .map_err(listen_error)?;
+
+    if resync {
+        let Some(sync) = sync.upgrade() else {
+            return Ok(());
+        };
+        sync.apply(SettingsChange::All).await;
+    }
+
+    loop {
+        // `None` means the connection was lost; reconnect here so the
+        // resync happens only once listening again
+        let Some(notification) = listener.try_recv().await.map_err(listen_error)? else {
+            return Err(Error::database("connection lost"));
+        };
+        let Some(sync) = sync.upgrade() else {
+            return Ok(());
+        };
+        match SettingsChange::parse(notification.payload()) {
+            Some(change) => sync.apply(change).await,
+            None => tracing::debug!("Ignoring malformed settings change notification"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn sync() -> SettingsSync {
+        SettingsSync::new(PgPool::connect_lazy("postgres://localhost/rustpress_test").unwrap())
+    }
+
+    #[test]
+    fn test_payload_round_trip() {
+        for change in [
+            SettingsChange::setting("page_cache"),
+            SettingsChange::site_option("blogname"),
+            SettingsChange::Allowlists,
+            SettingsChange::All,
+        ] {
+            assert_eq!(SettingsChange::parse(&change.payload()), Some(change));
+        }
+        // Keys may contain the separator
+        assert_eq!(
+            SettingsChange::parse("option:cdn:provider"),
+            Some(SettingsChange::site_option("cdn:provider"))
+        );
+        assert_eq!(SettingsChange::parse("setting:"), None);
+        assert_eq!(SettingsChange::parse("menu:main"), None);
+    }
+
+    #[tokio::test]
+    async fn test_apply_runs_matching_handlers() {
+        let sync = sync();
+        let page_cache = Arc::new(AtomicUsize::new(0));
+        let robots = Arc::new(AtomicUsize::new(0));
+        for (key, counter) in [("page_cache", &page_cache), ("robots_config", &robots)] {
+            sync.watch(
+                SettingsChange::setting(key),
+                counter.clone(),
+                |counter| async move {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                },
+            );
+        }
+        let mut changes = sync.subscribe();
+
+        sync.apply(SettingsChange::setting("page_cache")).await;
+        assert_eq!(page_cache.load(Ordering::SeqCst), 1);
+        assert_eq!(robots.load(Ordering::SeqCst), 0);
+
+        // Unwatched keys still reach subscribers
+        sync.apply(SettingsChange::site_option("blogname")).await;
+        sync.apply(SettingsChange::All).await;
+        assert_eq!(page_cache.load(Ordering::SeqCst), 2);
+        assert_eq!(robots.load(Ordering::SeqCst), 1);
+
+        assert_eq!(
+            changes.recv().await.unwrap(),
+            SettingsChange::setting("page_cache")
+        );
+        assert_eq!(
+            changes.recv().await.unwrap(),
+            SettingsChange::site_option("blogname")
+        );
+        assert_eq!(changes.recv().await.unwrap(), SettingsChange::All);
+    }
+}
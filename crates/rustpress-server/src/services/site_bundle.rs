@@ -0,0 +1,507 @@
+//! Site Export Bundles
+//!
+//! A bundle is the JSON manifest of a site export. Content datasets are
+//! streamed separately from `/api/v1/exports/{dataset}` and only listed
+//! here; the bundle itself carries plugin state. Plugins take part through
+//! a [`PluginDataExporter`], either returned by the plugin itself or
+//! registered on the service, and every payload is tagged with its schema
+//! version so bundles from older plugin releases can still be imported.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use rustpress_core::error::{Error, Result};
+use rustpress_core::plugin::{PluginDataExporter, PluginExport};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sqlx::PgPool;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use super::export_service::ExportDataset;
+
+/// Identifies RustPress site bundles
+pub const BUNDLE_FORMAT: &str = "rustpress-site";
+
+/// Current bundle layout version
+pub const BUNDLE_FORMAT_VERSION: u32 = 1;
+
+/// Manifest of a site export
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SiteBundle {
+    pub format: String,
+    pub format_version: u32,
+    /// RustPress version that wrote the bundle
+    pub generator: String,
+    pub exported_at: DateTime<Utc>,
+    /// Datasets exported separately, by name
+    #[serde(default)]
+    pub datasets: Vec<String>,
+    #[serde(default)]
+    pub plugins: Vec<PluginExport>,
+}
+
+impl SiteBundle {
+    /// Reject files that are not bundles or were written by a newer release
+    pub fn validate(&self) -> Result<()> {
+        if self.format != BUNDLE_FORMAT {
+            return Err(Error::invalid_input(
+                "format",
+                format!("Not a RustPress site bundle: '{}'", self.format),
+            ));
+        }
+        if self.format_version > BUNDLE_FORMAT_VERSION {
+            return Err(Error::invalid_input(
+                "format_version",
+                format!(
+                    "Bundle format {} is newer than the supported format {}",
+                    self.format_version, BUNDLE_FORMAT_VERSION
+                ),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// What happened to one plugin's data during an import
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PluginImportStatus {
+    Imported,
+    /// No exporter for the plugin on this site
+    Skipped,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginImportResult {
+    pub plugin_id: String,
+    pub schema_version: u32,
+    pub status: PluginImportStatus,
+    pub message: Option<String>,
+}
+
+/// Builds and restores site bundles
+pub struct SiteBundleService {
+    exporters: RwLock<Vec<Arc<dyn PluginDataExporter>>>,
+}
+
+impl SiteBundleService {
+    pub fn new() -> Self {
+        Self {
+            exporters: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// Register plugin data kept outside the plugin itself; it takes
+    /// precedence over an exporter the plugin provides
+    pub fn register(&self, exporter: Arc<dyn PluginDataExporter>) {
+        let mut exporters = self.exporters.write();
+        exporters.retain(|e| e.plugin_id() != exporter.plugin_id());
+        exporters.push(exporter);
+    }
+
+    /// Registered exporters followed by those of active plugins, one per
+    /// plugin
+    fn exporters(
+        &self,
+        from_plugins: Vec<Arc<dyn PluginDataExporter>>,
+    ) -> Vec<Arc<dyn PluginDataExporter>> {
+        let mut seen = HashSet::new();
+        self.exporters
+            .read()
+            .iter()
+            .cloned()
+            .chain(from_plugins)
+            .filter(|e| seen.insert(e.plugin_id().to_string()))
+            .collect()
+    }
+
+    /// Build a bundle; fails if any plugin cannot export, so bundles are
+    /// never silently incomplete
+    pub async fn export(
+        &self,
+        from_plugins: Vec<Arc<dyn PluginDataExporter>>,
+    ) -> Result<SiteBundle> {
+        let mut plugins = Vec::new();
+        for exporter in self.exporters(from_plugins) {
+            if let Some(data) = exporter.export().await? {
+                plugins.push(PluginExport {
+                    plugin_id: exporter.plugin_id().to_string(),
+                    schema_version: exporter.schema_version(),
+                    data,
+                });
+            }
+        }
+
+        Ok(SiteBundle {
+            format: BUNDLE_FORMAT.to_string(),
+            format_version: BUNDLE_FORMAT_VERSION,
+            generator: format!("RustPress {}", env!("CARGO_PKG_VERSION")),
+            exported_at: Utc::now(),
+            datasets: ExportDataset::ALL
+                .iter()
+                .map(|d| d.name().to_string())
+                .collect(),
+            plugins,
+        })
+    }
+
+    /// Hand each plugin payload to its import hook. One plugin failing does
+    /// not stop the others; the result says what happened to each.
+    pub async fn import(
+        &self,
+        from_plugins: Vec<Arc<dyn PluginDataExporter>>,
+        bundle: SiteBundle,
+    ) -> Result<Vec<PluginImportResult>> {
+        bundle.validate()?;
+        let exporters = self.exporters(from_plugins);
+
+        let mut results = Vec::with_capacity(bundle.plugins.len());
+        for export in bundle.plugins {
+            let exporter = exporters.iter().find(|e| e.plugin_id() == export.plugin_id);
+            let (status, message) = match exporter {
+                None => (
+                    PluginImportStatus::Skipped,
+                    Some("Plugin is not installed or not active".to_string()),
+                ),
+                Some(exporter) if export.schema_version > exporter.schema_version() => (
+                    PluginImportStatus::Failed,
+                    Some(format!(
+                        "Exported by a newer plugin release (schema {}, supported {})",
+                        export.schema_version,
+                        exporter.schema_version()
+                    )),
+                ),
+                Some(exporter) => match exporter.import(export.schema_version, export.data).await {
+                    Ok(()) => (PluginImportStatus::Imported, None),
+                    Err(e) => {
+                        tracing::warn!(
+                            plugin_id = %export.plugin_id,
+                            "Plugin import failed: {}",
+                            e
+                        );
+                        (PluginImportStatus::Failed, Some(e.to_string()))
+                    }
+                },
+            };
+            results.push(PluginImportResult {
+                plugin_id: export.plugin_id,
+                schema_version: export.schema_version,
+                status,
+                message,
+            });
+        }
+        Ok(results)
+    }
+}
+
+impl Default for SiteBundleService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Whether a settings key holds a credential
+fn is_secret_key(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    [
+        "secret",
+        "password",
+        "token",
+        "api_key",
+        "private_key",
+        "service_account",
+        "credentials",
+    ]
+    .iter()
+    .any(|needle| key.contains(needle))
+}
+
+/// Remove credentials from a settings value, at any depth
+fn strip_secrets(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            map.retain(|key, _| !is_secret_key(key));
+            map.values_mut().for_each(strip_secrets);
+        }
+        Value::Array(items) => items.iter_mut().for_each(strip_secrets),
+        _ => {}
+    }
+}
+
+/// RustAnalytics plugin ID
+const ANALYTICS_PLUGIN_ID: &str = "rustanalytics";
+
+/// Analytics tables carried in bundles, with the columns left out: run
+/// state, and users that may not exist on the importing site
+const ANALYTICS_TABLES: &[(&str, &str, &[&str])] = &[
+    (
+        "reports",
+        "rustanalytics_reports",
+        &[
+            "created_by",
+            "last_scheduled_run",
+            "next_scheduled_run",
+            "last_run_at",
+        ],
+    ),
+    ("goals", "rustanalytics_goals", &[]),
+    ("annotations", "rustanalytics_annotations", &["created_by"]),
+    (
+        "dashboards",
+        "rustanalytics_dashboards",
+        &["created_by", "shared_with"],
+    ),
+];
+
+/// RustAnalytics settings, custom reports, goals, annotations and
+/// dashboards
+///
+/// The plugin keeps this data in its own tables, which are read directly.
+/// Secrets are stripped on export and kept on import, so a bundle never
+/// overwrites the importing site's Google credentials.
+pub struct AnalyticsExporter {
+    pool: PgPool,
+}
+
+impl AnalyticsExporter {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    async fn table_exists(&self, table: &str) -> Result<bool> {
+        sqlx::query_scalar("SELECT to_regclass($1) IS NOT NULL")
+            .bind(table)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to inspect analytics tables", e))
+    }
+}
+
+#[async_trait]
+impl PluginDataExporter for AnalyticsExporter {
+    fn plugin_id(&self) -> &str {
+        ANALYTICS_PLUGIN_ID
+    }
+
+    fn schema_version(&self) -> u32 {
+        1
+    }
+
+    async fn export(&self) -> Result<Option<Value>> {
+        if !self.table_exists("rustanalytics_settings").await? {
+            return Ok(None);
+        }
+        let export_error = |e| Error::database_with_source("Failed to export analytics data", e);
+
+        let rows: Vec<(String, Value)> = sqlx::query_as(
+            "SELECT setting_key, setting_value FROM rustanalytics_settings ORDER BY setting_key",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(export_error)?;
+        let mut settings = Map::new();
+        for (key, mut value) in rows {
+            if is_secret_key(&key) {
+                continue;
+            }
+            strip_secrets(&mut value);
+            settings.insert(key, value);
+        }
+
+        let mut data = Map::new();
+        data.insert("settings".to_string(), Value::Object(settings));
+        for (name, table, excluded) in ANALYTICS_TABLES {
+            if !self.table_exists(table).await? {
+                continue;
+            }
+            let rows: Value = sqlx::query_scalar(&format!(
+                "SELECT COALESCE(jsonb_agg(to_jsonb(t) - $1::text[] \
+                 ORDER BY t.created_at, t.id), '[]') FROM {} t",
+                table
+            ))
+            .bind(excluded)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(export_error)?;
+            data.insert(name.to_string(), rows);
+        }
+        Ok(Some(Value::Object(data)))
+    }
+
+    async fn import(&self, _schema_version: u32, data: Value) -> Result<()> {
+        let import_error = |e| Error::database_with_source("Failed to import analytics data", e);
+        let mut tx = self.pool.begin().await.map_err(import_error)?;
+
+        if let Some(settings) = data.get("settings").and_then(Value::as_object) {
+            for (key, value) in settings {
+                if is_secret_key(key) {
+                    continue;
+                }
+                let mut value = value.clone();
+                strip_secrets(&mut value);
+                // Objects are merged so credentials kept on this site survive
+                sqlx::query(
+                    r#"
+                    INSERT INTO rustanalytics_settings (setting_key, setting_value)
+                    VALUES ($1, $2)
+                    ON CONFLICT (setting_key) DO UPDATE SET setting_value =
+                        CASE WHEN jsonb_typeof(rustanalytics_settings.setting_value) = 'object'
+                              AND jsonb_typeof(EXCLUDED.setting_value) = 'object'
+                            THEN rustanalytics_settings.setting_value || EXCLUDED.setting_value
+                            ELSE EXCLUDED.setting_value
+                        END
+                    "#,
+                )
+                .bind(key)
+                .bind(&value)
+                .execute(&mut *tx)
+                .await
+                .map_err(import_error)?;
+            }
+        }
+
+        // Rows are replaced by id, so importing the same bundle twice is
+        // harmless
+        for (name, table, _) in ANALYTICS_TABLES {
+            let Some(rows) = data.get(*name).filter(|rows| rows.is_array()) else {
+                continue;
+            };
+            sqlx::query(&format!(
+                "DELETE FROM {table} WHERE id IN \
+                 (SELECT id FROM jsonb_populate_recordset(NULL::{table}, $1))"
+            ))
+            .bind(rows)
+            .execute(&mut *tx)
+            .await
+            .map_err(import_error)?;
+            sqlx::query(&format!(
+                "INSERT INTO {table} SELECT * FROM jsonb_populate_recordset(NULL::{table}, $1)"
+            ))
+            .bind(rows)
+            .execute(&mut *tx)
+            .await
+            .map_err(import_error)?;
+        }
+
+        tx.commit().await.map_err(import_error)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parking_lot::Mutex;
+
+    struct MemoryExporter {
+        plugin_id: &'static str,
+        imported: Mutex<Vec<(u32, Value)>>,
+    }
+
+    impl MemoryExporter {
+        fn new(plugin_id: &'static str) -> Arc<Self> {
+            Arc::new(Self {
+                plugin_id,
+                imported: Mutex::new(Vec::new()),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl PluginDataExporter for MemoryExporter {
+        fn plugin_id(&self) -> &str {
+            self.plugin_id
+        }
+
+        fn schema_version(&self) -> u32 {
+            2
+        }
+
+        async fn export(&self) -> Result<Option<Value>> {
+            Ok(Some(serde_json::json!({ "from": self.plugin_id })))
+        }
+
+        async fn import(&self, schema_version: u32, data: Value) -> Result<()> {
+            self.imported.lock().push((schema_version, data));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_strip_secrets() {
+        let mut settings = serde_json::json!({
+            "ga_property_id": "123",
+            "ga_api_secret": "s3cret",
+            "service_account_json": "{}",
+            "custom_dimensions": [{ "name": "author", "access_token": "x" }],
+        });
+        strip_secrets(&mut settings);
+        assert_eq!(
+            settings,
+            serde_json::json!({
+                "ga_property_id": "123",
+                "custom_dimensions": [{ "name": "author" }],
+            })
+        );
+        assert!(is_secret_key("OAuth_Credentials"));
+        assert!(!is_secret_key("default_date_range"));
+    }
+
+    #[tokio::test]
+    async fn test_export_and_import_round_trip() {
+        let service = SiteBundleService::new();
+        let registered = MemoryExporter::new("forms");
+        service.register(registered.clone());
+        // The plugin's own exporter loses to the registered one
+        let from_plugin = MemoryExporter::new("forms");
+
+        let bundle = service.export(vec![from_plugin.clone()]).await.unwrap();
+        assert_eq!(bundle.format_version, BUNDLE_FORMAT_VERSION);
+        assert!(bundle.datasets.contains(&"posts".to_string()));
+        assert_eq!(bundle.plugins.len(), 1);
+        assert_eq!(bundle.plugins[0].schema_version, 2);
+
+        let mut bundle: SiteBundle =
+            serde_json::from_value(serde_json::to_value(&bundle).unwrap()).unwrap();
+        bundle.plugins.push(PluginExport {
+            plugin_id: "seo".to_string(),
+            schema_version: 1,
+            data: Value::Null,
+        });
+        bundle.plugins.push(PluginExport {
+            plugin_id: "forms".to_string(),
+            schema_version: 3,
+            data: Value::Null,
+        });
+
+        let results = service
+            .import(vec![from_plugin.clone()], bundle)
+            .await
+            .unwrap();
+        let statuses: Vec<_> = results.iter().map(|r| r.status).collect();
+        assert_eq!(
+            statuses,
+            [
+                PluginImportStatus::Imported,
+                PluginImportStatus::Skipped,
+                PluginImportStatus::Failed
+            ]
+        );
+        assert_eq!(registered.imported.lock().len(), 1);
+        assert!(from_plugin.imported.lock().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_rejects_newer_or_foreign_bundles() {
+        let service = SiteBundleService::new();
+        let mut bundle = service.export(Vec::new()).await.unwrap();
+        bundle.format_version = BUNDLE_FORMAT_VERSION + 1;
+        assert!(service.import(Vec::new(), bundle.clone()).await.is_err());
+
+        bundle.format_version = BUNDLE_FORMAT_VERSION;
+        bundle.format = "wxr".to_string();
+        assert!(service.import(Vec::new(), bundle).await.is_err());
+    }
+}
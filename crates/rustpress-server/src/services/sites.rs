@@ -0,0 +1,536 @@
+//! Sites of a network
+//!
+//! With multitenancy enabled, one server runs a network of sites sharing
+//! the database. Each request is served for one site, found by:
+//!
+//! - its own domain, whatever the identification strategy
+//! - a subdomain of the network domain, with `subdomain` identification
+//! - the first segment of the path, with `path` identification; the
+//!   prefix is stripped before routing, so sites keep the main site's URLs
+//! - the `x-tenant-id` header, with `header` identification, when a
+//!   trusted proxy sends it
+//!
+//! Requests naming no site are served by the main site. The resolved site
+//! is scoped to the task serving the request (see
+//! [`rustpress_core::tenant::with_site`]), which keeps repositories to its
+//! rows. Signed-in users act on a site only if they own it or were added
+//! to it. Sites get the themes the main site has installed when created,
+//! activate themes and plugins of their own, and are suspended or archived
+//! by network admins.
+
+use parking_lot::Mutex;
+use rustpress_core::config::{MultitenancyConfig, TenantIdentification};
+use rustpress_core::error::{Error, Result};
+use rustpress_database::repository::sites::{SaveSite, SiteRow, SitesRepository};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// How long a resolved address is reused; site changes made through this
+/// server take effect at once
+const RESOLVE_CACHE_TTL: Duration = Duration::from_secs(30);
+
+/// Most addresses kept resolved
+const RESOLVE_CACHE_SIZE: usize = 10_000;
+
+/// Longest site slug, the most a DNS label holds
+const MAX_SLUG: usize = 63;
+
+/// Slugs that would shadow the main site's paths or common hosts
+const RESERVED_SLUGS: &[&str] = &[
+    "admin",
+    "api",
+    "assets",
+    "feed",
+    "graphql",
+    "health",
+    "media",
+    "metrics",
+    "network",
+    "static",
+    "themes",
+    "uploads",
+    "wp-admin",
+    "wp-content",
+    "www",
+];
+
+/// Header naming the site with `header` identification; only honoured
+/// from trusted proxies
+pub const SITE_HEADER: &str = "x-tenant-id";
+
+/// Role of the users who manage the network and belong to every site
+pub const NETWORK_ADMIN_ROLE: &str = "super_admin";
+
+/// Lifecycle of a site
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SiteStatus {
+    Active,
+    /// Temporarily offline; answers 503
+    Suspended,
+    /// Permanently offline; answers 410
+    Archived,
+}
+
+impl SiteStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Active => "active",
+            Self::Suspended => "suspended",
+            Self::Archived => "archived",
+        }
+    }
+
+    pub fn parse(status: &str) -> Self {
+        match status {
+            "suspended" => Self::Suspended,
+            "archived" => Self::Archived,
+            _ => Self::Active,
+        }
+    }
+}
+
+/// A site to create, or its new settings
+#[derive(Debug, Clone, Deserialize)]
+pub struct SiteInput {
+    pub slug: String,
+    pub name: String,
+    /// Domain the site is served from besides its subdomain or path
+    pub domain: Option<String>,
+    /// Tenant whose allowlists apply; the slug when left out
+    pub tenant: Option<String>,
+    pub owner_id: Option<Uuid>,
+    /// Theme to activate on create, the main site's by default
+    pub theme: Option<String>,
+}
+
+impl SiteInput {
+    fn normalize(self) -> Result<SaveSite> {
+        let slug = self.slug.trim().to_lowercase();
+        validate_slug(&slug)?;
+        let name = self.name.trim().to_string();
+        if name.is_empty() {
+            return Err(Error::invalid_input("name", "Site name is required"));
+        }
+        let domain = match self.domain.as_deref().map(normalize_host) {
+            Some(domain) if domain.is_empty() => None,
+            Some(domain) if !valid_domain(&domain) => {
+                return Err(Error::invalid_input("domain", "Invalid domain name"))
+            }
+            domain => domain,
+        };
+        let tenant = self
+            .tenant
+            .map(|t| t.trim().to_string())
+            .filter(|t| !t.is_empty());
+        if tenant.as_ref().is_some_and(|t| t.len() > 100) {
+            return Err(Error::invalid_input(
+                "tenant",
+                "Tenant ids are at most 100 characters",
+            ));
+        }
+        Ok(SaveSite {
+            slug,
+            name,
+            domain,
+            tenant,
+            owner_id: self.owner_id,
+        })
+    }
+}
+
+/// Slugs are DNS labels: lowercase letters, digits and inner hyphens
+fn validate_slug(slug: &str) -> Result<()> {
+    let valid = !slug.is_empty()
+        && slug.len() <= MAX_SLUG
+        && slug
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        && !slug.starts_with('-')
+        && !slug.ends_with('-');
+    if !valid {
+        return Err(Error::invalid_input(
+            "slug",
+            "Site slugs are up to 63 lowercase letters, digits and hyphens",
+        ));
+    }
+    if RESERVED_SLUGS.contains(&slug) {
+        return Err(Error::invalid_input("slug", "This slug is reserved"));
+    }
+    Ok(())
+}
+
+fn valid_domain(domain: &str) -> bool {
+    domain.len() <= 253
+        && domain.contains('.')
+        && domain.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= MAX_SLUG
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+}
+
+/// Host without port or trailing dot, lowercased
+fn normalize_host(host: &str) -> String {
+    let host = host.trim();
+    let host = match host.rsplit_once(':') {
+        Some((name, port)) if port.chars().all(|c| c.is_ascii_digit()) => name,
+        _ => host,
+    };
+    host.trim_end_matches('.').to_lowercase()
+}
+
+/// Where a request says its site is
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SiteAddress {
+    /// Slug from the subdomain, path or header
+    pub slug: Option<String>,
+    /// Host the request was sent to, for sites on their own domain
+    pub domain: Option<String>,
+    /// Path prefix naming the site, stripped before routing
+    pub prefix: Option<String>,
+}
+
+impl SiteAddress {
+    /// Whether only an explicit slug names the site, so that the main
+    /// site must not answer in its place
+    pub fn names_site(&self) -> bool {
+        self.slug.is_some() && self.prefix.is_none()
+    }
+}
+
+/// Find where a request says its site is; None for the main site
+pub fn site_address(
+    config: &MultitenancyConfig,
+    host: Option<&str>,
+    path: &str,
+    header: Option<&str>,
+) -> Option<SiteAddress> {
+    let host = host.map(normalize_host).filter(|h| !h.is_empty());
+    let network = config.network_domain.as_deref().map(normalize_host);
+
+    let mut address = SiteAddress {
+        slug: None,
+        // The network domain itself is always the main site
+        domain: host.clone().filter(|h| Some(h) != network.as_ref()),
+        prefix: None,
+    };
+
+    match config.identification {
+        TenantIdentification::Subdomain => {
+            address.slug = host.as_deref().and_then(|host| match &network {
+                Some(network) => host
+                    .strip_suffix(network.as_str())
+                    .and_then(|rest| rest.strip_suffix('.'))
+                    .filter(|label| !label.contains('.'))
+                    .map(str::to_string),
+                None => {
+                    let labels: Vec<&str> = host.split('.').collect();
+                    (labels.len() > 2).then(|| labels[0].to_string())
+                }
+            });
+            if address.slug.is_some() {
+                address.domain = None;
+            }
+        }
+        TenantIdentification::Header => {
+            address.slug = header
+                .map(|h| h.trim().to_lowercase())
+                .filter(|h| !h.is_empty());
+        }
+        TenantIdentification::Path => {
+            let segment = path.trim_start_matches('/').split('/').next().unwrap_or("");
+            if validate_slug(segment).is_ok() {
+                address.slug = Some(segment.to_string());
+                address.prefix = Some(format!("/{}", segment));
+            }
+        }
+        // Tokens are read after routing, too late to pick the site
+        TenantIdentification::Jwt => {}
+    }
+
+    // Hosts such as www.example.com belong to the main site
+    if address
+        .slug
+        .as_deref()
+        .is_some_and(|slug| RESERVED_SLUGS.contains(&slug))
+    {
+        address.slug = None;
+        address.prefix = None;
+    }
+
+    (address.slug.is_some() || address.domain.is_some()).then_some(address)
+}
+
+/// Path with the site prefix removed; None when it doesn't start with it
+pub fn strip_site_prefix<'a>(path: &'a str, prefix: &str) -> Option<&'a str> {
+    match path.strip_prefix(prefix)? {
+        "" => Some("/"),
+        rest if rest.starts_with('/') => Some(rest),
+        _ => None,
+    }
+}
+
+/// Site a request is served for, as request extension
+#[derive(Debug, Clone)]
+pub struct CurrentSite(pub SiteRow);
+
+impl CurrentSite {
+    /// Tenant whose allowlists apply to the site
+    pub fn tenant(&self) -> &str {
+        self.0.tenant.as_deref().unwrap_or(&self.0.slug)
+    }
+
+    pub fn status(&self) -> SiteStatus {
+        SiteStatus::parse(&self.0.status)
+    }
+}
+
+/// Network sites service
+pub struct SiteService {
+    repo: SitesRepository,
+    resolved: Mutex<HashMap<SiteAddress, (Instant, Option<SiteRow>)>>,
+}
+
+impl SiteService {
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            repo: SitesRepository::new(pool),
+            resolved: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Site an address names, if any
+    pub async fn resolve(&self, address: &SiteAddress) -> Result<Option<SiteRow>> {
+        if let Some((resolved_at, site)) = self.resolved.lock().get(address) {
+            if resolved_at.elapsed() < RESOLVE_CACHE_TTL {
+                return Ok(site.clone());
+            }
+        }
+
+        let site = self
+            .repo
+            .find_by_address(address.slug.as_deref(), address.domain.as_deref())
+            .await?;
+
+        let mut resolved = self.resolved.lock();
+        if resolved.len() >= RESOLVE_CACHE_SIZE {
+            resolved.retain(|_, (resolved_at, _)| resolved_at.elapsed() < RESOLVE_CACHE_TTL);
+            if resolved.len() >= RESOLVE_CACHE_SIZE {
+                resolved.clear();
+            }
+        }
+        resolved.insert(address.clone(), (Instant::now(), site.clone()));
+        Ok(site)
+    }
+
+    fn invalidate(&self) {
+        self.resolved.lock().clear();
+    }
+
+    pub async fn list(&self) -> Result<Vec<SiteRow>> {
+        self.repo.list().await
+    }
+
+    pub async fn get(&self, id: Uuid) -> Result<SiteRow> {
+        self.repo
+            .find(id)
+            .await?
+            .ok_or_else(|| Error::not_found("Site", id.to_string()))
+    }
+
+    /// Create a site with the main site's themes
+    pub async fn create(&self, input: SiteInput) -> Result<SiteRow> {
+        let theme = input
+            .theme
+            .clone()
+            .map(|t| t.trim().to_string())
+            .filter(|t| !t.is_empty());
+        let site = input.normalize()?;
+        if let Some(theme) = &theme {
+            if !self.repo.theme_installed(theme).await? {
+                return Err(Error::invalid_input("theme", "Theme is not installed"));
+            }
+        }
+        let row = self.repo.create(&site, theme.as_deref()).await?;
+        self.invalidate();
+        Ok(row)
+    }
+
+    pub async fn update(&self, id: Uuid, input: SiteInput) -> Result<SiteRow> {
+        let site = input.normalize()?;
+        let row = self
+            .repo
+            .update(id, &site)
+            .await?
+            .ok_or_else(|| Error::not_found("Site", id.to_string()))?;
+        self.invalidate();
+        Ok(row)
+    }
+
+    /// Move a site to another status; archived sites must be activated
+    /// before they can be suspended
+    pub async fn set_status(
+        &self,
+        id: Uuid,
+        status: SiteStatus,
+        reason: Option<String>,
+    ) -> Result<SiteRow> {
+        let current = self.get(id).await?;
+        if status == SiteStatus::Suspended
+            && SiteStatus::parse(&current.status) == SiteStatus::Archived
+        {
+            return Err(Error::invalid_input(
+                "status",
+                "Archived sites cannot be suspended",
+            ));
+        }
+        let reason = reason
+            .map(|r| r.trim().to_string())
+            .filter(|r| !r.is_empty() && status != SiteStatus::Active);
+        let row = self
+            .repo
+            .set_status(id, status.as_str(), reason.as_deref())
+            .await?
+            .ok_or_else(|| Error::not_found("Site", id.to_string()))?;
+        self.invalidate();
+        Ok(row)
+    }
+
+    /// Plugins active on a site besides the network's
+    pub async fn plugins(&self, id: Uuid) -> Result<Vec<String>> {
+        self.repo.plugins(id).await
+    }
+
+    /// Activate or deactivate a plugin on a site; callers check that the
+    /// plugin exists and that the site may use it
+    pub async fn set_plugin(&self, id: Uuid, plugin_id: &str, active: bool) -> Result<()> {
+        self.get(id).await?;
+        self.repo.set_plugin(id, plugin_id, active).await
+    }
+
+    /// Whether a user may act on a site: its owner and the users added to
+    /// it. Network admins are let in by their role.
+    pub async fn is_member(&self, id: Uuid, user_id: Uuid) -> Result<bool> {
+        self.repo.is_member(id, user_id).await
+    }
+
+    /// Users added to a site besides its owner
+    pub async fn members(&self, id: Uuid) -> Result<Vec<Uuid>> {
+        self.get(id).await?;
+        self.repo.members(id).await
+    }
+
+    pub async fn set_member(&self, id: Uuid, user_id: Uuid, member: bool) -> Result<()> {
+        self.get(id).await?;
+        self.repo.set_member(id, user_id, member).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(identification: TenantIdentification, network: Option<&str>) -> MultitenancyConfig {
+        MultitenancyConfig {
+            enabled: true,
+            identification,
+            network_domain: network.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    fn address(slug: Option<&str>, domain: Option<&str>, prefix: Option<&str>) -> SiteAddress {
+        SiteAddress {
+            slug: slug.map(str::to_string),
+            domain: domain.map(str::to_string),
+            prefix: prefix.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_subdomain_of_network_domain() {
+        let config = config(TenantIdentification::Subdomain, Some("example.net"));
+
+        assert_eq!(
+            site_address(&config, Some("blog.example.net:8080"), "/", None),
+            Some(address(Some("blog"), None, None))
+        );
+        assert_eq!(site_address(&config, Some("example.net"), "/", None), None);
+        assert_eq!(
+            site_address(&config, Some("www.example.net"), "/", None),
+            None
+        );
+        assert_eq!(
+            site_address(&config, Some("Shop.Example.com."), "/", None),
+            Some(address(None, Some("shop.example.com"), None))
+        );
+    }
+
+    #[test]
+    fn test_subdomain_without_network_domain() {
+        let config = config(TenantIdentification::Subdomain, None);
+
+        assert_eq!(
+            site_address(&config, Some("blog.example.com"), "/", None),
+            Some(address(Some("blog"), None, None))
+        );
+        assert_eq!(
+            site_address(&config, Some("example.com"), "/", None),
+            Some(address(None, Some("example.com"), None))
+        );
+    }
+
+    #[test]
+    fn test_path_and_header_identification() {
+        let path = config(TenantIdentification::Path, Some("example.net"));
+        assert_eq!(
+            site_address(&path, Some("example.net"), "/blog/posts/hello", None),
+            Some(address(Some("blog"), None, Some("/blog")))
+        );
+        assert_eq!(
+            site_address(&path, Some("example.net"), "/api/v1/posts", None),
+            None
+        );
+        assert_eq!(site_address(&path, Some("example.net"), "/", None), None);
+
+        let header = config(TenantIdentification::Header, Some("example.net"));
+        assert_eq!(
+            site_address(&header, Some("example.net"), "/", Some("Blog")),
+            Some(address(Some("blog"), None, None))
+        );
+    }
+
+    #[test]
+    fn test_strip_site_prefix() {
+        assert_eq!(strip_site_prefix("/blog/posts", "/blog"), Some("/posts"));
+        assert_eq!(strip_site_prefix("/blog", "/blog"), Some("/"));
+        assert_eq!(strip_site_prefix("/blogroll", "/blog"), None);
+    }
+
+    #[test]
+    fn test_site_input_validation() {
+        let input = |slug: &str, domain: Option<&str>| SiteInput {
+            slug: slug.to_string(),
+            name: "Blog".to_string(),
+            domain: domain.map(str::to_string),
+            tenant: None,
+            owner_id: None,
+            theme: None,
+        };
+
+        let site = input(" Blog ", Some("Blog.Example.com"))
+            .normalize()
+            .unwrap();
+        assert_eq!(site.slug, "blog");
+        assert_eq!(site.domain.as_deref(), Some("blog.example.com"));
+
+        assert!(input("api", None).normalize().is_err());
+        assert!(input("-blog", None).normalize().is_err());
+        assert!(input("my_blog", None).normalize().is_err());
+        assert!(input("blog", Some("not a domain")).normalize().is_err());
+    }
+}
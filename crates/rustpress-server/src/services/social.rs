@@ -0,0 +1,2380 @@
+//! Social Sharing
+//!
+//! Share previews for posts and automatic posting to social networks.
+//!
+//! - every post has a 1200x630 preview card, rendered from an SVG Tera
+//!   template with the post's title, author, category colour and the site
+//!   logo. Since most networks won't show SVG images, a configured
+//!   rasterizer (an HTTP service, or one registered by a plugin) turns
+//!   them into PNGs; see [`super::og_image`] for how they are served
+//! - accounts on X, Mastodon, LinkedIn and Facebook are posted to by
+//!   [`SocialConnector`]s; plugins can replace a network's connector
+//! - each account has a message template (`{title} {url}`), which a post
+//!   can override, and messages are shortened to fit the network's limit
+//! - a share is one message to one account. Accounts with auto-posting
+//!   get a share queued when a post is published, after the account's
+//!   delay; shares can also be sent or scheduled by hand. Due shares of
+//!   published posts are sent by the `dispatch_social_shares` job, which
+//!   retries transient failures and logs every attempt on the share
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use regex::Regex;
+use rustpress_content::ContentAnalyzer;
+use rustpress_core::error::{Error, Result};
+use rustpress_core::http::HttpClient;
+use rustpress_events::subscriber::SubscriberConfig;
+use rustpress_events::{DomainEvent, EventBus, EventType, Subscriber};
+use rustpress_jobs::{JobHandler, JobPayload};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sqlx::types::Json;
+use sqlx::{FromRow, PgPool};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Arc, LazyLock};
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use super::change_feed::RowChange;
+use super::json_setting::JsonSetting;
+use super::og_image::og_image_path;
+use super::redirects::content_path;
+use super::RenderService;
+
+/// Settings key holding the social sharing configuration
+pub const SOCIAL_SETTINGS_KEY: &str = "social_config";
+
+/// Stored social sharing settings
+const SOCIAL_SETTING: JsonSetting<SocialConfig> =
+    JsonSetting::new(SOCIAL_SETTINGS_KEY, "social", "social sharing settings");
+
+/// Placeholder returned instead of stored access tokens
+const MASKED_SECRET: &str = "********";
+
+/// Preview card size, as networks recommend for `og:image`
+pub const CARD_WIDTH: u32 = 1200;
+pub const CARD_HEIGHT: u32 = 630;
+
+/// Horizontal margin of the built-in card design
+const CARD_MARGIN: u32 = 80;
+
+/// Longest accepted card or message template
+const MAX_TEMPLATE_LENGTH: usize = 64 * 1024;
+
+/// Longest message typed for a single share or post
+const MAX_MESSAGE_LENGTH: usize = 5_000;
+
+/// Longest card title or subtitle override
+const MAX_CARD_TEXT_LENGTH: usize = 200;
+
+/// Longest automatic-share delay (a week)
+const MAX_DELAY_MINUTES: u32 = 7 * 24 * 60;
+
+/// Sends of one share before it is given up on
+pub const MAX_ATTEMPTS: i32 = 5;
+
+/// Shares sent per dispatch run
+const DISPATCH_BATCH: i64 = 50;
+
+/// Posts published longer ago than this are never shared automatically
+const AUTO_POST_WINDOW_HOURS: i64 = 24;
+
+/// A share still `sending` after this long was interrupted
+const SENDING_TIMEOUT_MINUTES: i64 = 15;
+
+/// Tags turned into hashtags
+const MAX_HASHTAGS: usize = 3;
+
+/// Length networks count every link as
+const LINK_LENGTH: usize = 23;
+
+/// Largest error response body kept in a share's log
+const MAX_ERROR_LENGTH: usize = 300;
+
+/// Placeholders message templates may use
+const PLACEHOLDERS: [&str; 6] = ["title", "url", "excerpt", "author", "site", "hashtags"];
+
+static PLACEHOLDER_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\{([a-z_]+)\}").unwrap());
+
+static LINK_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"https?://\S+").unwrap());
+
+static ACCOUNT_ID_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[a-z0-9][a-z0-9_-]{0,63}$").unwrap());
+
+static COLOR_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^#(?:[0-9a-fA-F]{3}|[0-9a-fA-F]{6})$").unwrap());
+
+/// Built-in card design; see [`CardContext`] for the variables
+pub const DEFAULT_CARD_TEMPLATE: &str = r##"<svg xmlns="http://www.w3.org/2000/svg" width="{{ width }}" height="{{ height }}" viewBox="0 0 {{ width }} {{ height }}">
+  <rect width="{{ width }}" height="{{ height }}" fill="{{ background }}"/>
+  {% if image_url %}<image href="{{ image_url }}" width="{{ width }}" height="{{ height }}" preserveAspectRatio="xMidYMid slice" opacity="0.2"/>{% endif %}
+  <rect width="16" height="{{ height }}" fill="{{ category_color }}"/>
+  {% if logo_url %}<image href="{{ logo_url }}" x="{{ width - margin - 96 }}" y="62" width="96" height="96" preserveAspectRatio="xMaxYMid meet"/>{% endif %}
+  <text x="{{ margin }}" y="110" fill="{{ accent }}" font-family="system-ui, sans-serif" font-size="32" font-weight="600">{{ site_name }}</text>
+  {% if category %}<text x="{{ margin }}" y="{{ title_y - title_size - 24 }}" fill="{{ category_color }}" font-family="system-ui, sans-serif" font-size="26" font-weight="700" letter-spacing="2">{{ category | upper }}</text>{% endif %}
+  <text x="{{ margin }}" y="{{ title_y }}" fill="{{ foreground }}" font-family="system-ui, sans-serif" font-size="{{ title_size }}" font-weight="700">{% for line in title_lines %}<tspan x="{{ margin }}" dy="{% if loop.first %}0{% else %}{{ line_height }}{% endif %}">{{ line }}</tspan>{% endfor %}</text>
+  {% if subtitle %}<text x="{{ margin }}" y="{{ subtitle_y }}" fill="{{ foreground }}" opacity="0.85" font-family="system-ui, sans-serif" font-size="34">{{ subtitle }}</text>{% endif %}
+  <text x="{{ margin }}" y="{{ height - 70 }}" fill="{{ foreground }}" opacity="0.7" font-family="system-ui, sans-serif" font-size="28">{{ byline }}</text>
+</svg>"##;
+
+fn default_true() -> bool {
+    true
+}
+
+/// Networks posts can be shared to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SocialNetwork {
+    X,
+    Mastodon,
+    Linkedin,
+    Facebook,
+}
+
+impl SocialNetwork {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::X => "x",
+            Self::Mastodon => "mastodon",
+            Self::Linkedin => "linkedin",
+            Self::Facebook => "facebook",
+        }
+    }
+
+    /// Longest message the network accepts
+    pub fn max_length(&self) -> usize {
+        match self {
+            Self::X => 280,
+            Self::Mastodon => 500,
+            Self::Linkedin => 3_000,
+            Self::Facebook => 63_206,
+        }
+    }
+
+    /// Whether links count as [`LINK_LENGTH`] characters whatever their
+    /// real length
+    fn shortens_links(&self) -> bool {
+        matches!(self, Self::X | Self::Mastodon)
+    }
+
+    /// Template used when an account has none
+    pub fn default_template(&self) -> &'static str {
+        match self {
+            Self::X => "{title} {url} {hashtags}",
+            Self::Mastodon => "{title}\n\n{excerpt}\n\n{url}\n\n{hashtags}",
+            Self::Linkedin => "{title}\n\n{excerpt}\n\n{url}",
+            // The link is attached with its own preview
+            Self::Facebook => "{title}\n\n{excerpt}",
+        }
+    }
+}
+
+/// A social network account posts are shared to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SocialAccount {
+    /// Identifier shares and post messages refer to, e.g. `mastodon-main`
+    pub id: String,
+    pub network: SocialNetwork,
+    /// Name shown in the admin
+    #[serde(default)]
+    pub name: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Share posts when they are published
+    #[serde(default)]
+    pub auto_post: bool,
+    /// When auto-posting was turned on; posts published earlier are not
+    /// shared automatically. Set on save.
+    #[serde(default)]
+    pub auto_post_since: Option<DateTime<Utc>>,
+    /// Minutes between publication and the automatic share
+    #[serde(default)]
+    pub delay_minutes: u32,
+    /// Message template; the network's default when unset
+    #[serde(default)]
+    pub template: Option<String>,
+    /// Mastodon instance, e.g. `https://mastodon.social`
+    #[serde(default)]
+    pub instance_url: Option<String>,
+    /// LinkedIn author URN or Facebook page ID
+    #[serde(default)]
+    pub target: Option<String>,
+    #[serde(default)]
+    pub access_token: String,
+}
+
+impl SocialAccount {
+    pub fn template(&self) -> &str {
+        self.template
+            .as_deref()
+            .filter(|t| !t.trim().is_empty())
+            .unwrap_or_else(|| self.network.default_template())
+    }
+
+    fn validate(&self) -> Result<()> {
+        if !ACCOUNT_ID_RE.is_match(&self.id) {
+            return Err(Error::invalid_input(
+                "accounts",
+                format!(
+                    "Account ID '{}' must be up to 64 lowercase letters, digits, dashes or underscores",
+                    self.id
+                ),
+            ));
+        }
+        if self.enabled && self.access_token.is_empty() {
+            return Err(Error::invalid_input(
+                "accounts",
+                format!("Account '{}' needs an access token", self.id),
+            ));
+        }
+        if self.delay_minutes > MAX_DELAY_MINUTES {
+            return Err(Error::invalid_input(
+                "accounts",
+                format!("Delays can be at most {} minutes", MAX_DELAY_MINUTES),
+            ));
+        }
+        if let Some(template) = &self.template {
+            validate_message_template(template)?;
+        }
+
+        let target = self.target.as_deref().map(str::trim).unwrap_or_default();
+        match self.network {
+            SocialNetwork::Mastodon => {
+                let valid = self
+                    .instance_url
+                    .as_deref()
+                    .and_then(|url| reqwest::Url::parse(url).ok())
+                    .is_some_and(|url| url.scheme() == "https" && url.host_str().is_some());
+                if !valid {
+                    return Err(Error::invalid_input(
+                        "accounts",
+                        format!("Account '{}' needs an https:// instance URL", self.id),
+                    ));
+                }
+            }
+            SocialNetwork::Linkedin if !target.starts_with("urn:li:") => {
+                return Err(Error::invalid_input(
+                    "accounts",
+                    format!(
+                        "Account '{}' needs the author URN (urn:li:person:… or urn:li:organization:…)",
+                        self.id
+                    ),
+                ));
+            }
+            SocialNetwork::Facebook
+                if target.is_empty() || !target.chars().all(|c| c.is_ascii_digit()) =>
+            {
+                return Err(Error::invalid_input(
+                    "accounts",
+                    format!("Account '{}' needs the numeric page ID", self.id),
+                ));
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// Look of the preview cards
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CardSettings {
+    pub background: String,
+    pub foreground: String,
+    pub accent: String,
+    /// Site logo shown in the corner, absolute or site-relative
+    pub logo_url: Option<String>,
+    /// Colours of category labels and the accent bar by category slug;
+    /// others use the accent colour
+    pub category_colors: HashMap<String, String>,
+    /// SVG Tera template replacing the built-in design
+    pub template: Option<String>,
+}
+
+impl Default for CardSettings {
+    fn default() -> Self {
+        Self {
+            background: "#0f172a".to_string(),
+            foreground: "#f8fafc".to_string(),
+            accent: "#f97316".to_string(),
+            logo_url: None,
+            category_colors: HashMap::new(),
+            template: None,
+        }
+    }
+}
+
+impl CardSettings {
+    pub fn template(&self) -> &str {
+        self.template
+            .as_deref()
+            .filter(|t| !t.trim().is_empty())
+            .unwrap_or(DEFAULT_CARD_TEMPLATE)
+    }
+}
+
+/// Social sharing configuration
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SocialConfig {
+    pub accounts: Vec<SocialAccount>,
+    pub card: CardSettings,
+    /// Service that renders card SVGs as PNGs: it is sent the SVG as
+    /// `image/svg+xml` and answers with the image. Cards are served as SVG
+    /// without one.
+    pub rasterizer_url: Option<String>,
+}
+
+impl SocialConfig {
+    /// Load the configuration from settings, falling back to defaults
+    pub async fn load(pool: &PgPool) -> Result<Self> {
+        SOCIAL_SETTING.load_or_default(pool).await
+    }
+
+    /// Persist the configuration to settings
+    pub async fn save(&self, pool: &PgPool) -> Result<()> {
+        SOCIAL_SETTING.save(pool, self).await
+    }
+
+    pub fn account(&self, id: &str) -> Option<&SocialAccount> {
+        self.accounts.iter().find(|a| a.id == id)
+    }
+
+    /// Validate accounts, templates and colours
+    pub fn validate(&self) -> Result<()> {
+        let mut ids = BTreeSet::new();
+        for account in &self.accounts {
+            account.validate()?;
+            if !ids.insert(account.id.as_str()) {
+                return Err(Error::invalid_input(
+                    "accounts",
+                    format!("Account ID '{}' is used twice", account.id),
+                ));
+            }
+        }
+
+        for color in [
+            &self.card.background,
+            &self.card.foreground,
+            &self.card.accent,
+        ]
+        .into_iter()
+        .chain(self.card.category_colors.values())
+        {
+            if !COLOR_RE.is_match(color) {
+                return Err(Error::invalid_input(
+                    "card",
+                    format!("'{}' is not a #rgb or #rrggbb colour", color),
+                ));
+            }
+        }
+        if let Some(logo) = self.card.logo_url.as_deref().filter(|u| !u.is_empty()) {
+            let valid = logo.starts_with('/')
+                || reqwest::Url::parse(logo)
+                    .is_ok_and(|url| matches!(url.scheme(), "http" | "https"));
+            if !valid {
+                return Err(Error::invalid_input(
+                    "card.logo_url",
+                    "The logo must be an http(s) or site-relative URL",
+                ));
+            }
+        }
+        if let Some(template) = self.card.template.as_deref() {
+            if template.len() > MAX_TEMPLATE_LENGTH || !template.trim_start().starts_with("<svg") {
+                return Err(Error::invalid_input(
+                    "card.template",
+                    "Card templates must be an <svg> document of at most 64 KB",
+                ));
+            }
+            render_card(&self.card, &CardContext::sample())?;
+        }
+
+        if let Some(url) = self.rasterizer_url.as_deref().filter(|u| !u.is_empty()) {
+            let valid =
+                reqwest::Url::parse(url).is_ok_and(|url| matches!(url.scheme(), "http" | "https"));
+            if !valid {
+                return Err(Error::invalid_input(
+                    "rasterizer_url",
+                    "The rasterizer must be an http(s) URL",
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Copy safe to return from the API, with access tokens masked
+    pub fn masked(&self) -> Self {
+        let mut config = self.clone();
+        for account in &mut config.accounts {
+            if !account.access_token.is_empty() {
+                account.access_token = MASKED_SECRET.to_string();
+            }
+        }
+        config
+    }
+
+    /// Prepare an update: keep stored tokens sent back masked, and note
+    /// when auto-posting was turned on for each account.
+    ///
+    /// A stored token is only kept while the account still points at the
+    /// same network and instance; otherwise it would be sent to whichever
+    /// server the account was changed to, so a new token is required.
+    pub fn merge(&mut self, current: &SocialConfig, now: DateTime<Utc>) {
+        for account in &mut self.accounts {
+            let previous = current.account(&account.id);
+            if account.access_token == MASKED_SECRET {
+                account.access_token = previous
+                    .filter(|p| {
+                        p.network == account.network && p.instance_url == account.instance_url
+                    })
+                    .map(|p| p.access_token.clone())
+                    .unwrap_or_default();
+            }
+            account.auto_post_since = match previous {
+                _ if !account.auto_post => None,
+                Some(previous) if previous.auto_post => previous.auto_post_since.or(Some(now)),
+                _ => Some(now),
+            };
+        }
+    }
+}
+
+fn validate_message_template(template: &str) -> Result<()> {
+    if template.chars().count() > MAX_MESSAGE_LENGTH {
+        return Err(Error::invalid_input(
+            "template",
+            format!("Messages can be at most {} characters", MAX_MESSAGE_LENGTH),
+        ));
+    }
+    for caps in PLACEHOLDER_RE.captures_iter(template) {
+        if !PLACEHOLDERS.contains(&&caps[1]) {
+            return Err(Error::invalid_input(
+                "template",
+                format!(
+                    "Unknown placeholder {{{}}}; use one of {}",
+                    &caps[1],
+                    PLACEHOLDERS.map(|p| format!("{{{}}}", p)).join(", ")
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Values substituted into message templates
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ShareContext {
+    pub title: String,
+    pub url: String,
+    pub excerpt: String,
+    pub author: String,
+    pub site: String,
+    pub hashtags: Vec<String>,
+}
+
+impl ShareContext {
+    fn substitute(&self, template: &str, title: &str, excerpt: &str, hashtags: bool) -> String {
+        let text = PLACEHOLDER_RE.replace_all(template, |caps: &regex::Captures| match &caps[1] {
+            "title" => title.to_string(),
+            "url" => self.url.clone(),
+            "excerpt" => excerpt.to_string(),
+            "author" => self.author.clone(),
+            "site" => self.site.clone(),
+            "hashtags" if hashtags => self.hashtags.join(" "),
+            "hashtags" => String::new(),
+            _ => caps[0].to_string(),
+        });
+        tidy(&text)
+    }
+}
+
+/// Drop the blank lines and spaces left by empty placeholders
+fn tidy(text: &str) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        let line = line
+            .split(' ')
+            .filter(|w| !w.is_empty())
+            .collect::<Vec<_>>();
+        let line = line.join(" ");
+        if line.is_empty() && lines.last().is_none_or(|l| l.is_empty()) {
+            continue;
+        }
+        lines.push(line);
+    }
+    while lines.last().is_some_and(|l| l.is_empty()) {
+        lines.pop();
+    }
+    lines.join("\n")
+}
+
+/// Length of a message as the network counts it
+pub fn message_length(network: SocialNetwork, text: &str) -> usize {
+    if !network.shortens_links() {
+        return text.chars().count();
+    }
+    let links: usize = LINK_RE
+        .find_iter(text)
+        .map(|m| m.as_str().chars().count())
+        .sum();
+    let count = LINK_RE.find_iter(text).count();
+    text.chars().count() - links + count * LINK_LENGTH
+}
+
+/// Cut text to at most `max` characters, ending with an ellipsis
+fn shorten(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    if max == 0 {
+        return String::new();
+    }
+    let cut: String = text.chars().take(max - 1).collect();
+    let cut = match cut.rfind(' ') {
+        Some(space) if space > cut.len() / 2 => &cut[..space],
+        _ => cut.as_str(),
+    };
+    format!("{}…", cut.trim_end_matches([' ', '.', ',', ';', ':']))
+}
+
+/// Fill a template, shortening the excerpt, then dropping hashtags, then
+/// shortening the title until the message fits the network
+pub fn render_message(network: SocialNetwork, template: &str, context: &ShareContext) -> String {
+    let limit = network.max_length();
+    let fits = |text: &str| message_length(network, text) <= limit;
+
+    let full = context.substitute(template, &context.title, &context.excerpt, true);
+    if fits(&full) {
+        return full;
+    }
+
+    for hashtags in [true, false] {
+        let bare = context.substitute(template, &context.title, "", hashtags);
+        let room = limit.saturating_sub(message_length(network, &bare));
+        if room > 0 && !context.excerpt.is_empty() && template.contains("{excerpt}") {
+            // Rendering adds no separator of its own around the excerpt
+            let text = context.substitute(
+                template,
+                &context.title,
+                &shorten(&context.excerpt, room),
+                hashtags,
+            );
+            if fits(&text) && room > 20 {
+                return text;
+            }
+        }
+        if fits(&bare) {
+            return bare;
+        }
+    }
+
+    let bare = context.substitute(template, "", "", false);
+    let room = limit.saturating_sub(message_length(network, &bare));
+    let text = context.substitute(template, &shorten(&context.title, room), "", false);
+    if fits(&text) {
+        return text;
+    }
+    shorten(&text, limit)
+}
+
+/// `#CamelCase` hashtags for tag names; tags without letters or digits
+/// are skipped
+pub fn hashtags<'a>(tags: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut seen = BTreeSet::new();
+    tags.into_iter()
+        .filter_map(|tag| {
+            let tag: String = tag
+                .split(|c: char| !c.is_alphanumeric())
+                .filter(|w| !w.is_empty())
+                .map(|w| {
+                    let mut chars = w.chars();
+                    chars
+                        .next()
+                        .map(|c| c.to_uppercase().chain(chars).collect::<String>())
+                        .unwrap_or_default()
+                })
+                .collect();
+            (!tag.is_empty() && seen.insert(tag.to_lowercase())).then(|| format!("#{}", tag))
+        })
+        .take(MAX_HASHTAGS)
+        .collect()
+}
+
+/// Variables of the card template
+#[derive(Debug, Clone, Serialize)]
+pub struct CardContext {
+    pub width: u32,
+    pub height: u32,
+    pub margin: u32,
+    pub background: String,
+    pub foreground: String,
+    pub accent: String,
+    pub site_name: String,
+    /// Title wrapped to fit the card
+    pub title_lines: Vec<String>,
+    pub title: String,
+    pub title_size: u32,
+    pub line_height: u32,
+    /// Baseline of the first title line
+    pub title_y: u32,
+    pub subtitle: Option<String>,
+    pub subtitle_y: u32,
+    pub author: Option<String>,
+    /// Formatted publication date
+    pub date: Option<String>,
+    /// Author and publication date
+    pub byline: String,
+    /// Name of the post's primary category
+    pub category: Option<String>,
+    /// Colour of the category, or the accent colour
+    pub category_color: String,
+    /// Absolute URL of the featured image
+    pub image_url: Option<String>,
+    /// Absolute URL of the site logo
+    pub logo_url: Option<String>,
+}
+
+impl CardContext {
+    pub fn new(
+        settings: &CardSettings,
+        site_name: &str,
+        title: &str,
+        subtitle: Option<&str>,
+        author: Option<&str>,
+        date: Option<&str>,
+        image_url: Option<String>,
+    ) -> Self {
+        let text_width = (CARD_WIDTH - 2 * CARD_MARGIN) as f32;
+        // Rough average glyph width of a bold sans-serif face
+        let fitting = [(72, 3), (60, 4), (52, 5)]
+            .into_iter()
+            .map(|(size, max_lines)| {
+                let per_line = (text_width / (size as f32 * 0.55)) as usize;
+                (size, wrap_text(title, per_line, max_lines))
+            });
+        let mut sizes = fitting.collect::<Vec<_>>();
+        let (title_size, title_lines) = match sizes.iter().position(|(_, (_, cut))| !cut) {
+            Some(i) => sizes.swap_remove(i),
+            None => sizes.pop().unwrap_or_default(),
+        };
+        let title_lines = title_lines.0;
+
+        let line_height = title_size * 6 / 5;
+        let block = line_height * title_lines.len().saturating_sub(1) as u32 + title_size;
+        let title_y = CARD_HEIGHT / 2 + title_size - block / 2 - 10;
+        let byline = [author, date]
+            .into_iter()
+            .flatten()
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join(" · ");
+        Self {
+            width: CARD_WIDTH,
+            height: CARD_HEIGHT,
+            margin: CARD_MARGIN,
+            background: settings.background.clone(),
+            foreground: settings.foreground.clone(),
+            accent: settings.accent.clone(),
+            site_name: site_name.to_string(),
+            title_lines,
+            title: title.to_string(),
+            title_size,
+            line_height,
+            title_y,
+            subtitle: subtitle.map(|s| shorten(s, 60)),
+            subtitle_y: title_y + block - title_size + 70,
+            author: author.map(str::to_string),
+            date: date.map(str::to_string),
+            byline,
+            category: None,
+            category_color: settings.accent.clone(),
+            image_url,
+            logo_url: None,
+        }
+    }
+
+    /// Label the card with a category, in its configured colour
+    pub fn with_category(mut self, settings: &CardSettings, slug: &str, name: &str) -> Self {
+        if let Some(color) = settings.category_colors.get(slug) {
+            self.category_color = color.clone();
+        }
+        self.category = Some(name.to_string());
+        self
+    }
+
+    /// Show the site logo, given as an absolute URL
+    pub fn with_logo(mut self, logo_url: Option<String>) -> Self {
+        self.logo_url = logo_url;
+        self
+    }
+
+    /// Stand-in post used to check templates
+    fn sample() -> Self {
+        Self::new(
+            &CardSettings::default(),
+            "Example Site",
+            "An example post title that is long enough to wrap",
+            Some("Subtitle"),
+            Some("Jane Doe"),
+            Some("January 1, 2025"),
+            Some("https://example.com/image.jpg".to_string()),
+        )
+        .with_category(&CardSettings::default(), "news", "News")
+        .with_logo(Some("https://example.com/logo.png".to_string()))
+    }
+}
+
+/// Wrap text into at most `max_lines` lines of `width` characters; the
+/// flag is set when text had to be cut
+fn wrap_text(text: &str, width: usize, max_lines: usize) -> (Vec<String>, bool) {
+    let width = width.max(1);
+    let mut lines: Vec<String> = Vec::new();
+    let mut current = String::new();
+    for word in text.split_whitespace() {
+        let mut word = word.to_string();
+        while word.chars().count() > width {
+            if !current.is_empty() {
+                lines.push(std::mem::take(&mut current));
+            }
+            let split = word
+                .char_indices()
+                .nth(width)
+                .map_or(word.len(), |(i, _)| i);
+            lines.push(word[..split].to_string());
+            word = word[split..].to_string();
+        }
+        if current.is_empty() {
+            current = word;
+        } else if current.chars().count() + 1 + word.chars().count() <= width {
+            current.push(' ');
+            current.push_str(&word);
+        } else {
+            lines.push(std::mem::replace(&mut current, word));
+        }
+    }
+    if !current.is_empty() {
+        lines.push(current);
+    }
+
+    if lines.len() <= max_lines {
+        return (lines, false);
+    }
+    lines.truncate(max_lines);
+    if let Some(last) = lines.last_mut() {
+        let kept: String = last.chars().take(width.saturating_sub(1)).collect();
+        *last = format!("{}…", kept.trim_end());
+    }
+    (lines, true)
+}
+
+/// Render a card's SVG; values are XML-escaped by the template engine
+pub fn render_card(settings: &CardSettings, card: &CardContext) -> Result<String> {
+    let context = tera::Context::from_serialize(card)
+        .map_err(|e| Error::internal(format!("Invalid card context: {}", e)))?;
+    tera::Tera::one_off(settings.template(), &context, true).map_err(|e| {
+        let detail = std::error::Error::source(&e)
+            .map(|source| source.to_string())
+            .unwrap_or_else(|| e.to_string());
+        Error::invalid_input("card.template", format!("Card template failed: {}", detail))
+    })
+}
+
+/// Preview card bytes
+#[derive(Debug, Clone)]
+pub struct CardImage {
+    pub content_type: String,
+    pub bytes: Bytes,
+}
+
+/// Turns card SVGs into images networks display
+#[async_trait]
+pub trait CardRasterizer: Send + Sync {
+    async fn rasterize(&self, svg: &str) -> Result<CardImage>;
+}
+
+/// Rasterizes cards by posting them to an HTTP rendering service
+pub struct HttpCardRasterizer {
+    client: HttpClient,
+    url: String,
+}
+
+impl HttpCardRasterizer {
+    pub fn new(client: HttpClient, url: impl Into<String>) -> Self {
+        Self {
+            client,
+            url: url.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl CardRasterizer for HttpCardRasterizer {
+    async fn rasterize(&self, svg: &str) -> Result<CardImage> {
+        let request = self
+            .client
+            .post(&self.url)
+            .header(reqwest::header::CONTENT_TYPE, "image/svg+xml")
+            .header(reqwest::header::ACCEPT, "image/png")
+            .query(&[("width", CARD_WIDTH), ("height", CARD_HEIGHT)])
+            .body(svg.to_string());
+        let response = self.client.send(request).await?;
+        if !response.status().is_success() {
+            return Err(Error::ServiceUnavailable {
+                service: format!("card rasterizer ({})", response.status()),
+            });
+        }
+
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("image/png")
+            .to_string();
+        if !content_type.starts_with("image/") {
+            return Err(Error::internal(format!(
+                "Card rasterizer answered with {}",
+                content_type
+            )));
+        }
+        let bytes = response
+            .bytes()
+            .await
+            .map_err(|e| Error::internal(format!("Failed to read rasterized card: {}", e)))?;
+        Ok(CardImage {
+            content_type,
+            bytes,
+        })
+    }
+}
+
+/// What a connector is asked to post
+#[derive(Debug, Clone)]
+pub struct OutgoingShare<'a> {
+    /// Share ID, usable as an idempotency key
+    pub id: Uuid,
+    pub message: &'a str,
+    pub link: &'a str,
+    pub title: &'a str,
+    pub description: &'a str,
+}
+
+/// Where a share was posted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublishedShare {
+    pub remote_id: String,
+    pub remote_url: Option<String>,
+}
+
+/// A share that could not be posted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShareFailure {
+    pub message: String,
+    /// Worth trying again later (rate limits, network and server errors)
+    pub retryable: bool,
+}
+
+impl ShareFailure {
+    fn permanent(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            retryable: false,
+        }
+    }
+}
+
+/// Posts shares to one network
+#[async_trait]
+pub trait SocialConnector: Send + Sync {
+    fn network(&self) -> SocialNetwork;
+
+    async fn publish(
+        &self,
+        account: &SocialAccount,
+        share: &OutgoingShare<'_>,
+    ) -> std::result::Result<PublishedShare, ShareFailure>;
+}
+
+/// Send a request and return the response headers and JSON body (null
+/// when there is none), classifying failures
+async fn send_share(
+    client: &HttpClient,
+    request: reqwest::RequestBuilder,
+) -> std::result::Result<(reqwest::header::HeaderMap, Value), ShareFailure> {
+    let response = client.send(request).await.map_err(|e| ShareFailure {
+        message: e.to_string(),
+        retryable: true,
+    })?;
+    let status = response.status();
+    let headers = response.headers().clone();
+    let body = response.text().await.unwrap_or_default();
+
+    if !status.is_success() {
+        let detail: String = body.chars().take(MAX_ERROR_LENGTH).collect();
+        return Err(ShareFailure {
+            message: format!("{}: {}", status, detail.trim()),
+            retryable: status.as_u16() == 429 || status.is_server_error(),
+        });
+    }
+    let body = serde_json::from_str(&body).unwrap_or(Value::Null);
+    Ok((headers, body))
+}
+
+/// ID field of a response body, as a string whatever its JSON type
+fn remote_id(value: &Value) -> Option<String> {
+    match value {
+        Value::String(id) => Some(id.clone()),
+        Value::Number(id) => Some(id.to_string()),
+        _ => None,
+    }
+}
+
+/// X (Twitter) API v2, with an OAuth 2.0 user access token
+pub struct XConnector {
+    client: HttpClient,
+}
+
+impl XConnector {
+    pub fn new(client: HttpClient) -> Self {
+        Self { client }
+    }
+}
+
+#[async_trait]
+impl SocialConnector for XConnector {
+    fn network(&self) -> SocialNetwork {
+        SocialNetwork::X
+    }
+
+    async fn publish(
+        &self,
+        account: &SocialAccount,
+        share: &OutgoingShare<'_>,
+    ) -> std::result::Result<PublishedShare, ShareFailure> {
+        let request = self
+            .client
+            .post("https://api.twitter.com/2/tweets")
+            .bearer_auth(&account.access_token)
+            .json(&json!({ "text": share.message }));
+        let (_, body) = send_share(&self.client, request).await?;
+        let id = remote_id(&body["data"]["id"])
+            .ok_or_else(|| ShareFailure::permanent("X did not return a post ID"))?;
+        Ok(PublishedShare {
+            remote_url: Some(format!("https://x.com/i/web/status/{}", id)),
+            remote_id: id,
+        })
+    }
+}
+
+/// Mastodon statuses API
+pub struct MastodonConnector {
+    client: HttpClient,
+}
+
+impl MastodonConnector {
+    pub fn new(client: HttpClient) -> Self {
+        Self { client }
+    }
+}
+
+#[async_trait]
+impl SocialConnector for MastodonConnector {
+    fn network(&self) -> SocialNetwork {
+        SocialNetwork::Mastodon
+    }
+
+    async fn publish(
+        &self,
+        account: &SocialAccount,
+        share: &OutgoingShare<'_>,
+    ) -> std::result::Result<PublishedShare, ShareFailure> {
+        let instance = account
+            .instance_url
+            .as_deref()
+            .ok_or_else(|| ShareFailure::permanent("No Mastodon instance configured"))?;
+        let request = self
+            .client
+            .post(format!(
+                "{}/api/v1/statuses",
+                instance.trim_end_matches('/')
+            ))
+            .bearer_auth(&account.access_token)
+            // Retries of the same share don't post it twice
+            .header("Idempotency-Key", share.id.to_string())
+            .json(&json!({ "status": share.message, "visibility": "public" }));
+        let (_, body) = send_share(&self.client, request).await?;
+        let id = remote_id(&body["id"])
+            .ok_or_else(|| ShareFailure::permanent("Mastodon did not return a status ID"))?;
+        Ok(PublishedShare {
+            remote_id: id,
+            remote_url: body["url"].as_str().map(str::to_string),
+        })
+    }
+}
+
+/// Characters LinkedIn's commentary format reserves
+const LINKEDIN_RESERVED: &[char] = &[
+    '\\', '|', '{', '}', '@', '[', ']', '(', ')', '<', '>', '#', '*', '_', '~',
+];
+
+/// LinkedIn Posts API
+pub struct LinkedinConnector {
+    client: HttpClient,
+}
+
+impl LinkedinConnector {
+    /// API version sent in the `LinkedIn-Version` header
+    const API_VERSION: &'static str = "202405";
+
+    pub fn new(client: HttpClient) -> Self {
+        Self { client }
+    }
+
+    fn escape(text: &str) -> String {
+        let mut escaped = String::with_capacity(text.len());
+        for c in text.chars() {
+            if LINKEDIN_RESERVED.contains(&c) {
+                escaped.push('\\');
+            }
+            escaped.push(c);
+        }
+        escaped
+    }
+}
+
+#[async_trait]
+impl SocialConnector for LinkedinConnector {
+    fn network(&self) -> SocialNetwork {
+        SocialNetwork::Linkedin
+    }
+
+    async fn publish(
+        &self,
+        account: &SocialAccount,
+        share: &OutgoingShare<'_>,
+    ) -> std::result::Result<PublishedShare, ShareFailure> {
+        let author = account
+            .target
+            .as_deref()
+            .ok_or_else(|| ShareFailure::permanent("No LinkedIn author configured"))?;
+        let request = self
+            .client
+            .post("https://api.linkedin.com/rest/posts")
+            .bearer_auth(&account.access_token)
+            .header("LinkedIn-Version", Self::API_VERSION)
+            .header("X-Restli-Protocol-Version", "2.0.0")
+            .json(&json!({
+                "author": author,
+                "commentary": Self::escape(share.message),
+                "visibility": "PUBLIC",
+                "distribution": {
+                    "feedDistribution": "MAIN_FEED",
+                    "targetEntities": [],
+                    "thirdPartyDistributionChannels": [],
+                },
+                "content": {
+                    "article": {
+                        "source": share.link,
+                        "title": share.title,
+                        "description": share.description,
+                    },
+                },
+                "lifecycleState": "PUBLISHED",
+                "isReshareDisabledByAuthor": false,
+            }));
+        let (headers, _) = send_share(&self.client, request).await?;
+        let id = headers
+            .get("x-restli-id")
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+            .ok_or_else(|| ShareFailure::permanent("LinkedIn did not return a post ID"))?;
+        Ok(PublishedShare {
+            remote_url: Some(format!("https://www.linkedin.com/feed/update/{}", id)),
+            remote_id: id,
+        })
+    }
+}
+
+/// Facebook Graph API page feed, with a page access token
+pub struct FacebookConnector {
+    client: HttpClient,
+}
+
+impl FacebookConnector {
+    const GRAPH_URL: &'static str = "https://graph.facebook.com/v19.0";
+
+    pub fn new(client: HttpClient) -> Self {
+        Self { client }
+    }
+}
+
+#[async_trait]
+impl SocialConnector for FacebookConnector {
+    fn network(&self) -> SocialNetwork {
+        SocialNetwork::Facebook
+    }
+
+    async fn publish(
+        &self,
+        account: &SocialAccount,
+        share: &OutgoingShare<'_>,
+    ) -> std::result::Result<PublishedShare, ShareFailure> {
+        let page = account
+            .target
+            .as_deref()
+            .ok_or_else(|| ShareFailure::permanent("No Facebook page configured"))?;
+        let request = self
+            .client
+            .post(format!("{}/{}/feed", Self::GRAPH_URL, page))
+            .form(&[
+                ("message", share.message),
+                ("link", share.link),
+                ("access_token", account.access_token.as_str()),
+            ]);
+        let (_, body) = send_share(&self.client, request).await?;
+        let id = remote_id(&body["id"])
+            .ok_or_else(|| ShareFailure::permanent("Facebook did not return a post ID"))?;
+        Ok(PublishedShare {
+            remote_url: Some(format!("https://www.facebook.com/{}", id)),
+            remote_id: id,
+        })
+    }
+}
+
+/// Sharing settings of one post
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct PostSocialSettings {
+    pub post_id: Uuid,
+    /// Replaces the post title on the card
+    pub card_title: Option<String>,
+    /// Line shown under the title on the card
+    pub card_subtitle: Option<String>,
+    /// Share the post automatically when it is published
+    pub auto_post: bool,
+    /// Messages by account ID, replacing the account's template
+    pub messages: Json<HashMap<String, String>>,
+    pub updated_by: Option<Uuid>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl PostSocialSettings {
+    fn defaults(post_id: Uuid) -> Self {
+        Self {
+            post_id,
+            card_title: None,
+            card_subtitle: None,
+            auto_post: true,
+            messages: Json(HashMap::new()),
+            updated_by: None,
+            updated_at: None,
+        }
+    }
+}
+
+/// Sharing settings of a post as submitted
+#[derive(Debug, Clone, Deserialize)]
+pub struct PostSocialInput {
+    pub card_title: Option<String>,
+    pub card_subtitle: Option<String>,
+    #[serde(default = "default_true")]
+    pub auto_post: bool,
+    #[serde(default)]
+    pub messages: HashMap<String, String>,
+}
+
+impl PostSocialInput {
+    fn normalize(mut self) -> Result<Self> {
+        for (field, value) in [
+            ("card_title", &mut self.card_title),
+            ("card_subtitle", &mut self.card_subtitle),
+        ] {
+            *value = value
+                .take()
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty());
+            if value
+                .as_ref()
+                .is_some_and(|v| v.chars().count() > MAX_CARD_TEXT_LENGTH)
+            {
+                return Err(Error::invalid_input(
+                    field,
+                    format!("Must be at most {} characters", MAX_CARD_TEXT_LENGTH),
+                ));
+            }
+        }
+        self.messages
+            .retain(|_, message| !message.trim().is_empty());
+        for message in self.messages.values() {
+            validate_message_template(message)?;
+        }
+        Ok(self)
+    }
+}
+
+/// A message as it would be posted to one account
+#[derive(Debug, Clone, Serialize)]
+pub struct MessagePreview {
+    pub account_id: String,
+    pub network: SocialNetwork,
+    pub name: String,
+    pub message: String,
+    /// Length as the network counts it
+    pub length: usize,
+    pub max_length: usize,
+    pub auto_post: bool,
+}
+
+/// How a post looks when shared
+#[derive(Debug, Clone, Serialize)]
+pub struct SharePreview {
+    pub url: String,
+    pub title: String,
+    pub description: String,
+    /// Absolute URL of the preview card
+    pub card_url: String,
+    pub messages: Vec<MessagePreview>,
+}
+
+/// A post's sharing settings, preview and shares
+#[derive(Debug, Clone, Serialize)]
+pub struct PostSocialOverview {
+    pub settings: PostSocialSettings,
+    pub preview: SharePreview,
+    pub shares: Vec<SocialShare>,
+}
+
+/// Lifecycle of a share
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ShareStatus {
+    Pending,
+    Sending,
+    Sent,
+    Failed,
+    Cancelled,
+}
+
+impl ShareStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Sending => "sending",
+            Self::Sent => "sent",
+            Self::Failed => "failed",
+            Self::Cancelled => "cancelled",
+        }
+    }
+}
+
+/// One attempt to post a share
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShareAttempt {
+    pub at: DateTime<Utc>,
+    pub success: bool,
+    /// Where it was posted, or why it failed
+    pub message: String,
+}
+
+/// A message to one account about one post
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct SocialShare {
+    pub id: Uuid,
+    pub post_id: Uuid,
+    pub account_id: String,
+    pub network: String,
+    /// Text given for this share; rendered from the templates when sent
+    /// otherwise
+    pub message: Option<String>,
+    /// Queued by auto-posting rather than by hand
+    pub automatic: bool,
+    pub status: String,
+    pub scheduled_at: DateTime<Utc>,
+    pub attempts: i32,
+    pub remote_id: Option<String>,
+    pub remote_url: Option<String>,
+    pub last_error: Option<String>,
+    pub log: Json<Vec<ShareAttempt>>,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub sent_at: Option<DateTime<Utc>>,
+}
+
+const SHARE_COLUMNS: &str = "id, post_id, account_id, network, message, automatic, status, \
+     scheduled_at, attempts, remote_id, remote_url, last_error, log, created_by, created_at, \
+     updated_at, sent_at";
+
+/// Shares to send now or later
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ShareRequest {
+    /// Accounts to share to; every enabled account when empty
+    #[serde(default)]
+    pub account_ids: Vec<String>,
+    /// Text replacing the templates, placeholders allowed
+    pub message: Option<String>,
+    /// When to send; now when unset. Shares of unpublished posts wait
+    /// until the post is published.
+    pub scheduled_at: Option<DateTime<Utc>>,
+}
+
+/// Share list filters
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ShareQuery {
+    pub post_id: Option<Uuid>,
+    pub account_id: Option<String>,
+    pub status: Option<ShareStatus>,
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+}
+
+/// Outcome of a dispatch run
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DispatchReport {
+    pub queued: u32,
+    pub sent: u32,
+    /// Failed for good
+    pub failed: u32,
+    /// Failed, to be tried again
+    pub retrying: u32,
+}
+
+/// A post as sharing needs it
+#[derive(Debug, Clone, FromRow)]
+struct SharePost {
+    id: Uuid,
+    title: String,
+    slug: String,
+    excerpt: Option<String>,
+    content: Option<String>,
+    status: String,
+    post_type: String,
+    published_at: Option<DateTime<Utc>>,
+    updated_at: DateTime<Utc>,
+    author_name: Option<String>,
+    image_url: Option<String>,
+}
+
+impl SharePost {
+    fn description(&self) -> String {
+        let text = match self.excerpt.as_deref().filter(|e| !e.trim().is_empty()) {
+            Some(excerpt) => excerpt.to_string(),
+            None => ContentAnalyzer::new().generate_excerpt(self.content.as_deref().unwrap_or("")),
+        };
+        let text = rustpress_content::unescape_html(&rustpress_content::strip_tags(&text));
+        text.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+}
+
+/// Make a site-relative URL absolute
+fn absolute_url(site_url: &str, url: &str) -> String {
+    if url.starts_with("http://") || url.starts_with("https://") {
+        url.to_string()
+    } else {
+        format!(
+            "{}/{}",
+            site_url.trim_end_matches('/'),
+            url.trim_start_matches('/')
+        )
+    }
+}
+
+/// Preview cards, connectors and shares
+pub struct SocialService {
+    pool: PgPool,
+    client: HttpClient,
+    renderer: Arc<RenderService>,
+    config: RwLock<Option<Arc<SocialConfig>>>,
+    connectors: parking_lot::RwLock<HashMap<SocialNetwork, Arc<dyn SocialConnector>>>,
+    rasterizer: parking_lot::RwLock<Option<Arc<dyn CardRasterizer>>>,
+    events: Option<Arc<EventBus>>,
+}
+
+impl SocialService {
+    pub fn new(pool: PgPool, client: HttpClient, renderer: Arc<RenderService>) -> Self {
+        let connectors: Vec<Arc<dyn SocialConnector>> = vec![
+            Arc::new(XConnector::new(client.clone())),
+            Arc::new(MastodonConnector::new(client.clone())),
+            Arc::new(LinkedinConnector::new(client.clone())),
+            Arc::new(FacebookConnector::new(client.clone())),
+        ];
+        Self {
+            pool,
+            client,
+            renderer,
+            config: RwLock::new(None),
+            connectors: parking_lot::RwLock::new(
+                connectors.into_iter().map(|c| (c.network(), c)).collect(),
+            ),
+            rasterizer: parking_lot::RwLock::new(None),
+            events: None,
+        }
+    }
+
+    /// Publish `social.share_sent` and `social.share_failed` events on the bus
+    pub fn with_events(mut self, events: Arc<EventBus>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Replace the connector of a network
+    pub fn register_connector(&self, connector: Arc<dyn SocialConnector>) {
+        self.connectors
+            .write()
+            .insert(connector.network(), connector);
+    }
+
+    /// Rasterize cards with this instead of the configured service
+    pub fn set_rasterizer(&self, rasterizer: Arc<dyn CardRasterizer>) {
+        *self.rasterizer.write() = Some(rasterizer);
+    }
+
+    /// Social configuration, loaded from settings on first use
+    pub async fn config(&self) -> Arc<SocialConfig> {
+        if let Some(config) = self.config.read().await.clone() {
+            return config;
+        }
+
+        match SocialConfig::load(&self.pool).await {
+            Ok(config) => {
+                let config = Arc::new(config);
+                *self.config.write().await = Some(config.clone());
+                config
+            }
+            Err(e) => {
+                tracing::warn!("Failed to load social settings, sharing is off: {}", e);
+                Arc::new(SocialConfig::default())
+            }
+        }
+    }
+
+    /// Validate and save a new configuration, returning it masked
+    pub async fn update_config(&self, mut config: SocialConfig) -> Result<SocialConfig> {
+        let current = self.config().await;
+        config.merge(&current, Utc::now());
+        config.validate()?;
+        config.save(&self.pool).await?;
+        *self.config.write().await = Some(Arc::new(config.clone()));
+        Ok(config.masked())
+    }
+
+    /// Drop the cached configuration; the next use reloads it
+    pub async fn invalidate(&self) {
+        *self.config.write().await = None;
+    }
+
+    async fn load_post(&self, post_id: Uuid) -> Result<SharePost> {
+        sqlx::query_as(
+            "SELECT p.id, p.title, p.slug, p.excerpt, p.content, p.status::text AS status, \
+             p.post_type::text AS post_type, p.published_at, p.updated_at, \
+             u.display_name AS author_name, m.url AS image_url \
+             FROM posts p \
+             LEFT JOIN users u ON u.id = p.author_id \
+             LEFT JOIN media m ON m.id = p.featured_image_id AND m.deleted_at IS NULL \
+             WHERE p.id = $1 AND p.deleted_at IS NULL",
+        )
+        .bind(post_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load post", e))?
+        .ok_or_else(|| Error::not_found("Post", post_id.to_string()))
+    }
+
+    /// Name and slug of the post's first category
+    async fn load_category(&self, post_id: Uuid) -> Result<Option<(String, String)>> {
+        sqlx::query_as(
+            "SELECT t.slug, t.name FROM term_relationships tr \
+             JOIN terms t ON t.id = tr.term_id \
+             JOIN taxonomies tax ON tax.id = t.taxonomy_id \
+             WHERE tr.object_id = $1 AND tr.object_type = 'post' AND tax.slug = 'category' \
+             ORDER BY tr.term_order LIMIT 1",
+        )
+        .bind(post_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load category", e))
+    }
+
+    async fn load_tags(&self, post_id: Uuid) -> Result<Vec<String>> {
+        sqlx::query_scalar(
+            "SELECT t.name FROM term_relationships tr \
+             JOIN terms t ON t.id = tr.term_id \
+             JOIN taxonomies tax ON tax.id = t.taxonomy_id \
+             WHERE tr.object_id = $1 AND tr.object_type = 'post' AND tax.slug = 'post_tag' \
+             ORDER BY tr.term_order",
+        )
+        .bind(post_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load tags", e))
+    }
+
+    /// A post's sharing settings, or the defaults
+    pub async fn post_settings(&self, post_id: Uuid) -> Result<PostSocialSettings> {
+        let settings = sqlx::query_as(
+            "SELECT post_id, card_title, card_subtitle, auto_post, messages, updated_by, \
+             updated_at FROM social_post_settings WHERE post_id = $1",
+        )
+        .bind(post_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load sharing settings", e))?;
+        Ok(settings.unwrap_or_else(|| PostSocialSettings::defaults(post_id)))
+    }
+
+    /// Replace a post's sharing settings
+    pub async fn save_post_settings(
+        &self,
+        post_id: Uuid,
+        user_id: Uuid,
+        input: PostSocialInput,
+    ) -> Result<PostSocialSettings> {
+        let input = input.normalize()?;
+        let config = self.config().await;
+        if let Some(unknown) = input
+            .messages
+            .keys()
+            .find(|id| config.account(id).is_none())
+        {
+            return Err(Error::invalid_input(
+                "messages",
+                format!("There is no account '{}'", unknown),
+            ));
+        }
+        self.load_post(post_id).await?;
+
+        sqlx::query_as(
+            "INSERT INTO social_post_settings \
+             (post_id, card_title, card_subtitle, auto_post, messages, updated_by, updated_at) \
+             VALUES ($1, $2, $3, $4, $5, $6, NOW()) \
+             ON CONFLICT (post_id) DO UPDATE SET card_title = $2, card_subtitle = $3, \
+             auto_post = $4, messages = $5, updated_by = $6, updated_at = NOW() \
+             RETURNING post_id, card_title, card_subtitle, auto_post, messages, updated_by, \
+             updated_at",
+        )
+        .bind(post_id)
+        .bind(&input.card_title)
+        .bind(&input.card_subtitle)
+        .bind(input.auto_post)
+        .bind(Json(&input.messages))
+        .bind(user_id)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to save sharing settings", e))
+    }
+
+    async fn context(&self, post: &SharePost) -> Result<ShareContext> {
+        let site = self.renderer.site_info().await;
+        let tags = self.load_tags(post.id).await?;
+        Ok(ShareContext {
+            title: post.title.clone(),
+            url: absolute_url(&site.url, &content_path(&post.post_type, &post.slug)),
+            excerpt: post.description(),
+            author: post.author_name.clone().unwrap_or_default(),
+            site: site.name,
+            hashtags: hashtags(tags.iter().map(String::as_str)),
+        })
+    }
+
+    /// The text to post to an account: the share's own message, the post's
+    /// message for the account, or the account's template
+    fn message(
+        account: &SocialAccount,
+        settings: &PostSocialSettings,
+        context: &ShareContext,
+        own: Option<&str>,
+    ) -> String {
+        let template = own
+            .or_else(|| settings.messages.get(&account.id).map(String::as_str))
+            .unwrap_or_else(|| account.template());
+        render_message(account.network, template, context)
+    }
+
+    /// How a post looks when shared to each enabled account
+    pub async fn preview(&self, post_id: Uuid) -> Result<SharePreview> {
+        let post = self.load_post(post_id).await?;
+        let settings = self.post_settings(post_id).await?;
+        let context = self.context(&post).await?;
+        let config = self.config().await;
+        let site_url = self.renderer.site_url().await;
+
+        let messages = config
+            .accounts
+            .iter()
+            .filter(|a| a.enabled)
+            .map(|account| {
+                let message = Self::message(account, &settings, &context, None);
+                MessagePreview {
+                    account_id: account.id.clone(),
+                    network: account.network,
+                    name: account.name.clone(),
+                    length: message_length(account.network, &message),
+                    max_length: account.network.max_length(),
+                    auto_post: account.auto_post && settings.auto_post,
+                    message,
+                }
+            })
+            .collect();
+
+        Ok(SharePreview {
+            url: context.url.clone(),
+            title: settings.card_title.clone().unwrap_or(context.title.clone()),
+            description: shorten(&context.excerpt, 200),
+            card_url: absolute_url(
+                &site_url,
+                &og_image_path(
+                    post.id,
+                    // As serialized for templates, so both give the same URL
+                    &post.updated_at.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+                ),
+            ),
+            messages,
+        })
+    }
+
+    /// Settings, preview and shares of a post
+    pub async fn overview(&self, post_id: Uuid) -> Result<PostSocialOverview> {
+        let preview = self.preview(post_id).await?;
+        let (shares, _) = self
+            .list(&ShareQuery {
+                post_id: Some(post_id),
+                per_page: Some(100),
+                ..Default::default()
+            })
+            .await?;
+        Ok(PostSocialOverview {
+            settings: self.post_settings(post_id).await?,
+            preview,
+            shares,
+        })
+    }
+
+    /// Render a post's preview card as SVG; only published posts unless
+    /// `unpublished` is set
+    pub async fn card_svg(&self, post_id: Uuid, unpublished: bool) -> Result<String> {
+        let post = self.load_post(post_id).await?;
+        if !unpublished && post.status != "published" {
+            return Err(Error::not_found("Post", post_id.to_string()));
+        }
+        let settings = self.post_settings(post_id).await?;
+        let category = self.load_category(post_id).await?;
+        let config = self.config().await;
+        let site = self.renderer.site_info().await;
+
+        let title = settings.card_title.as_deref().unwrap_or(&post.title);
+        let date = post
+            .published_at
+            .map(|at| at.format("%B %-d, %Y").to_string());
+        let mut card = CardContext::new(
+            &config.card,
+            &site.name,
+            title,
+            settings.card_subtitle.as_deref(),
+            post.author_name.as_deref(),
+            date.as_deref(),
+            post.image_url
+                .as_deref()
+                .map(|url| absolute_url(&site.url, url)),
+        )
+        .with_logo(
+            config
+                .card
+                .logo_url
+                .as_deref()
+                .filter(|url| !url.is_empty())
+                .map(|url| absolute_url(&site.url, url)),
+        );
+        if let Some((slug, name)) = &category {
+            card = card.with_category(&config.card, slug, name);
+        }
+        render_card(&config.card, &card)
+    }
+
+    /// Rasterizer of preview cards: the registered one, else the
+    /// configured service; cards stay SVG without one
+    pub async fn rasterizer(&self) -> Option<Arc<dyn CardRasterizer>> {
+        if let Some(rasterizer) = self.rasterizer.read().clone() {
+            return Some(rasterizer);
+        }
+        let config = self.config().await;
+        config
+            .rasterizer_url
+            .as_deref()
+            .filter(|url| !url.is_empty())
+            .map(|url| {
+                Arc::new(HttpCardRasterizer::new(self.client.clone(), url))
+                    as Arc<dyn CardRasterizer>
+            })
+    }
+
+    /// Queue shares of a post by hand
+    pub async fn share(
+        &self,
+        post_id: Uuid,
+        user_id: Uuid,
+        request: ShareRequest,
+    ) -> Result<Vec<SocialShare>> {
+        let config = self.config().await;
+        let accounts: Vec<&SocialAccount> = if request.account_ids.is_empty() {
+            config.accounts.iter().filter(|a| a.enabled).collect()
+        } else {
+            request
+                .account_ids
+                .iter()
+                .map(|id| {
+                    config.account(id).filter(|a| a.enabled).ok_or_else(|| {
+                        Error::invalid_input(
+                            "account_ids",
+                            format!("There is no enabled account '{}'", id),
+                        )
+                    })
+                })
+                .collect::<Result<_>>()?
+        };
+        if accounts.is_empty() {
+            return Err(Error::validation("No social accounts are enabled"));
+        }
+        let message = request
+            .message
+            .as_deref()
+            .map(str::trim)
+            .filter(|m| !m.is_empty());
+        if let Some(message) = message {
+            validate_message_template(message)?;
+        }
+        let post = self.load_post(post_id).await?;
+        if post.status == "trash" {
+            return Err(Error::validation("Trashed posts can't be shared"));
+        }
+        let scheduled_at = request.scheduled_at.unwrap_or_else(Utc::now);
+
+        let mut shares = Vec::with_capacity(accounts.len());
+        for account in accounts {
+            let share = sqlx::query_as(&format!(
+                "INSERT INTO social_shares \
+                 (id, post_id, account_id, network, message, scheduled_at, created_by) \
+                 VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING {}",
+                SHARE_COLUMNS
+            ))
+            .bind(Uuid::now_v7())
+            .bind(post_id)
+            .bind(&account.id)
+            .bind(account.network.as_str())
+            .bind(message)
+            .bind(scheduled_at)
+            .bind(user_id)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to queue share", e))?;
+            shares.push(share);
+        }
+        Ok(shares)
+    }
+
+    /// Queue automatic shares of a post that has just been published;
+    /// returns how many were queued
+    pub async fn queue_automatic(&self, post_id: Uuid) -> Result<u32> {
+        let config = self.config().await;
+        if !config.accounts.iter().any(|a| a.enabled && a.auto_post) {
+            return Ok(0);
+        }
+        let post = match self.load_post(post_id).await {
+            Ok(post) => post,
+            Err(Error::NotFound { .. }) => return Ok(0),
+            Err(e) => return Err(e),
+        };
+        let Some(published_at) = post.published_at else {
+            return Ok(0);
+        };
+        let now = Utc::now();
+        if post.status != "published"
+            || post.post_type != "post"
+            || published_at < now - Duration::hours(AUTO_POST_WINDOW_HOURS)
+            || !self.post_settings(post_id).await?.auto_post
+        {
+            return Ok(0);
+        }
+
+        let mut queued = 0;
+        for account in config.accounts.iter().filter(|a| a.enabled && a.auto_post) {
+            if account
+                .auto_post_since
+                .is_none_or(|since| published_at < since)
+            {
+                continue;
+            }
+            let scheduled_at = published_at + Duration::minutes(account.delay_minutes as i64);
+            let result = sqlx::query(
+                "INSERT INTO social_shares \
+                 (id, post_id, account_id, network, automatic, scheduled_at) \
+                 VALUES ($1, $2, $3, $4, TRUE, $5) \
+                 ON CONFLICT (post_id, account_id) WHERE automatic DO NOTHING",
+            )
+            .bind(Uuid::now_v7())
+            .bind(post_id)
+            .bind(&account.id)
+            .bind(account.network.as_str())
+            .bind(scheduled_at)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to queue share", e))?;
+            queued += result.rows_affected() as u32;
+        }
+        Ok(queued)
+    }
+
+    /// Queue automatic shares of recently published posts that were
+    /// missed, e.g. posts published by the scheduler
+    async fn queue_recent(&self, config: &SocialConfig) -> Result<u32> {
+        let window = Utc::now() - Duration::hours(AUTO_POST_WINDOW_HOURS);
+        let mut posts = BTreeSet::new();
+        for account in config.accounts.iter().filter(|a| a.enabled && a.auto_post) {
+            let Some(since) = account.auto_post_since else {
+                continue;
+            };
+            let ids: Vec<Uuid> = sqlx::query_scalar(
+                "SELECT p.id FROM posts p \
+                 WHERE p.status::text = 'published' AND p.post_type::text IN ('post', 'episode', 'event') \
+                 AND p.deleted_at IS NULL AND p.published_at >= $1 \
+                 AND NOT EXISTS (SELECT 1 FROM social_shares s WHERE s.post_id = p.id \
+                     AND s.account_id = $2 AND s.automatic)",
+            )
+            .bind(since.max(window))
+            .bind(&account.id)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to find posts to share", e))?;
+            posts.extend(ids);
+        }
+
+        let mut queued = 0;
+        for post_id in posts {
+            queued += self.queue_automatic(post_id).await?;
+        }
+        Ok(queued)
+    }
+
+    /// Send due shares of published posts
+    pub async fn dispatch_due(&self) -> Result<DispatchReport> {
+        let config = self.config().await;
+        let mut report = DispatchReport {
+            queued: self.queue_recent(&config).await?,
+            ..Default::default()
+        };
+
+        // A share left `sending` by a crashed node may or may not have
+        // been posted; rather than risk posting twice, give up on it
+        let interrupted: Vec<(Uuid,)> = sqlx::query_as(
+            "UPDATE social_shares SET status = 'failed', \
+             last_error = 'Interrupted while sending', updated_at = NOW() \
+             WHERE status = 'sending' AND updated_at < $1 RETURNING id",
+        )
+        .bind(Utc::now() - Duration::minutes(SENDING_TIMEOUT_MINUTES))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to expire shares", e))?;
+        report.failed += interrupted.len() as u32;
+
+        let due: Vec<SocialShare> = sqlx::query_as(&format!(
+            "UPDATE social_shares SET status = 'sending', attempts = attempts + 1, \
+             updated_at = NOW() \
+             WHERE id IN (SELECT s.id FROM social_shares s JOIN posts p ON p.id = s.post_id \
+                 WHERE s.status = 'pending' AND s.scheduled_at <= NOW() \
+                 AND p.status::text = 'published' AND p.deleted_at IS NULL \
+                 ORDER BY s.scheduled_at LIMIT $1 FOR UPDATE OF s SKIP LOCKED) \
+             RETURNING {}",
+            SHARE_COLUMNS
+        ))
+        .bind(DISPATCH_BATCH)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to claim shares", e))?;
+
+        for share in due {
+            let result = self.send(&config, &share).await;
+            match self.record(&share, result).await? {
+                ShareStatus::Sent => report.sent += 1,
+                ShareStatus::Pending => report.retrying += 1,
+                _ => report.failed += 1,
+            }
+        }
+        Ok(report)
+    }
+
+    async fn send(
+        &self,
+        config: &SocialConfig,
+        share: &SocialShare,
+    ) -> std::result::Result<PublishedShare, ShareFailure> {
+        let account = config
+            .account(&share.account_id)
+            .filter(|a| a.enabled)
+            .ok_or_else(|| {
+                ShareFailure::permanent(format!(
+                    "Account '{}' is no longer enabled",
+                    share.account_id
+                ))
+            })?;
+        let connector = self
+            .connectors
+            .read()
+            .get(&account.network)
+            .cloned()
+            .ok_or_else(|| ShareFailure::permanent("No connector for this network"))?;
+
+        let prepared = async {
+            let post = self.load_post(share.post_id).await?;
+            let settings = self.post_settings(share.post_id).await?;
+            let context = self.context(&post).await?;
+            Ok::<_, Error>((settings, context))
+        };
+        let (settings, context) = prepared.await.map_err(|e| ShareFailure {
+            message: e.to_string(),
+            retryable: true,
+        })?;
+        let message = Self::message(account, &settings, &context, share.message.as_deref());
+        let title = settings.card_title.as_deref().unwrap_or(&context.title);
+        let description = shorten(&context.excerpt, 200);
+
+        connector
+            .publish(
+                account,
+                &OutgoingShare {
+                    id: share.id,
+                    message: &message,
+                    link: &context.url,
+                    title,
+                    description: &description,
+                },
+            )
+            .await
+    }
+
+    /// Store the outcome of a send and announce it; returns the new status
+    async fn record(
+        &self,
+        share: &SocialShare,
+        result: std::result::Result<PublishedShare, ShareFailure>,
+    ) -> Result<ShareStatus> {
+        let now = Utc::now();
+        let (status, event) = match &result {
+            Ok(published) => {
+                sqlx::query(
+                    "UPDATE social_shares SET status = 'sent', remote_id = $2, remote_url = $3, \
+                     last_error = NULL, sent_at = $4, updated_at = $4, log = log || $5 \
+                     WHERE id = $1",
+                )
+                .bind(share.id)
+                .bind(&published.remote_id)
+                .bind(&published.remote_url)
+                .bind(now)
+                .bind(Json(vec![ShareAttempt {
+                    at: now,
+                    success: true,
+                    message: published
+                        .remote_url
+                        .clone()
+                        .unwrap_or_else(|| published.remote_id.clone()),
+                }]))
+                .execute(&self.pool)
+                .await
+                .map_err(|e| Error::database_with_source("Failed to record share", e))?;
+                (ShareStatus::Sent, "social.share_sent")
+            }
+            Err(failure) => {
+                let status = if failure.retryable && share.attempts < MAX_ATTEMPTS {
+                    ShareStatus::Pending
+                } else {
+                    ShareStatus::Failed
+                };
+                // Retries back off: 2, 4, 8, ... minutes
+                let retry_at = now + Duration::minutes(1 << share.attempts.clamp(1, 10));
+                sqlx::query(
+                    "UPDATE social_shares SET status = $2, last_error = $3, updated_at = $4, \
+                     scheduled_at = CASE WHEN $2 = 'pending' THEN $5 ELSE scheduled_at END, \
+                     log = log || $6 WHERE id = $1",
+                )
+                .bind(share.id)
+                .bind(status.as_str())
+                .bind(&failure.message)
+                .bind(now)
+                .bind(retry_at)
+                .bind(Json(vec![ShareAttempt {
+                    at: now,
+                    success: false,
+                    message: failure.message.clone(),
+                }]))
+                .execute(&self.pool)
+                .await
+                .map_err(|e| Error::database_with_source("Failed to record share", e))?;
+                if status == ShareStatus::Pending {
+                    return Ok(status);
+                }
+                (status, "social.share_failed")
+            }
+        };
+
+        if let Some(events) = &self.events {
+            let event = DomainEvent::new(
+                event,
+                json!({
+                    "id": share.id,
+                    "post_id": share.post_id,
+                    "account_id": share.account_id,
+                    "network": share.network,
+                    "automatic": share.automatic,
+                    "remote_url": result.as_ref().ok().and_then(|p| p.remote_url.clone()),
+                    "error": result.as_ref().err().map(|f| f.message.clone()),
+                }),
+            )
+            .with_aggregate(share.post_id, "post");
+            if let Err(e) = events.publish(event).await {
+                tracing::warn!(share_id = %share.id, error = %e, "Failed to publish share event");
+            }
+        }
+        Ok(status)
+    }
+
+    /// Shares, newest first
+    pub async fn list(&self, query: &ShareQuery) -> Result<(Vec<SocialShare>, u64)> {
+        let page = query.page.unwrap_or(1).max(1);
+        let per_page = query.per_page.unwrap_or(20).clamp(1, 100);
+        let status = query.status.map(|s| s.as_str());
+
+        const FILTER: &str = "WHERE ($1::uuid IS NULL OR post_id = $1) \
+             AND ($2::text IS NULL OR account_id = $2) AND ($3::text IS NULL OR status = $3)";
+
+        let total: i64 =
+            sqlx::query_scalar(&format!("SELECT COUNT(*) FROM social_shares {}", FILTER))
+                .bind(query.post_id)
+                .bind(&query.account_id)
+                .bind(status)
+                .fetch_one(&self.pool)
+                .await
+                .map_err(|e| Error::database_with_source("Failed to count shares", e))?;
+
+        let shares = sqlx::query_as(&format!(
+            "SELECT {} FROM social_shares {} ORDER BY created_at DESC, id DESC \
+             LIMIT $4 OFFSET $5",
+            SHARE_COLUMNS, FILTER
+        ))
+        .bind(query.post_id)
+        .bind(&query.account_id)
+        .bind(status)
+        .bind(per_page as i64)
+        .bind(((page - 1) * per_page) as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to list shares", e))?;
+
+        Ok((shares, total as u64))
+    }
+
+    pub async fn get(&self, id: Uuid) -> Result<SocialShare> {
+        sqlx::query_as(&format!(
+            "SELECT {} FROM social_shares WHERE id = $1",
+            SHARE_COLUMNS
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load share", e))?
+        .ok_or_else(|| Error::not_found("Share", id.to_string()))
+    }
+
+    /// Move a share from one status to another
+    async fn transition(
+        &self,
+        id: Uuid,
+        from: ShareStatus,
+        to: ShareStatus,
+        action: &str,
+    ) -> Result<SocialShare> {
+        let share = sqlx::query_as(&format!(
+            "UPDATE social_shares SET status = $3, updated_at = NOW(), \
+             scheduled_at = CASE WHEN $3 = 'pending' THEN NOW() ELSE scheduled_at END \
+             WHERE id = $1 AND status = $2 RETURNING {}",
+            SHARE_COLUMNS
+        ))
+        .bind(id)
+        .bind(from.as_str())
+        .bind(to.as_str())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to update share", e))?;
+
+        match share {
+            Some(share) => Ok(share),
+            None => {
+                let share = self.get(id).await?;
+                Err(Error::validation(format!(
+                    "Only {} shares can be {}; this one is {}",
+                    from.as_str(),
+                    action,
+                    share.status
+                )))
+            }
+        }
+    }
+
+    /// Cancel a share that has not been sent
+    pub async fn cancel(&self, id: Uuid) -> Result<SocialShare> {
+        self.transition(
+            id,
+            ShareStatus::Pending,
+            ShareStatus::Cancelled,
+            "cancelled",
+        )
+        .await
+    }
+
+    /// Send a failed share again at the next dispatch
+    pub async fn retry(&self, id: Uuid) -> Result<SocialShare> {
+        self.transition(id, ShareStatus::Failed, ShareStatus::Pending, "retried")
+            .await
+    }
+
+    /// Queue automatic shares as soon as the change feed reports a post
+    /// write; the dispatch job catches up on anything missed
+    pub fn subscribe(self: &Arc<Self>, bus: &EventBus) {
+        let event_types = ["created", "updated"]
+            .iter()
+            .map(|op| EventType::new(format!("change.post.{}", op)))
+            .collect();
+
+        let social = Arc::downgrade(self);
+        bus.subscribe(Subscriber::new(
+            "social_auto_post",
+            SubscriberConfig::new(event_types).async_handler(),
+            move |event| {
+                let social = social.clone();
+                async move {
+                    let Some(social) = social.upgrade() else {
+                        return Ok(());
+                    };
+                    let Ok(change) = serde_json::from_value::<RowChange>(event.payload.clone())
+                    else {
+                        return Ok(());
+                    };
+                    let Some(post_id) = change.id else {
+                        return Ok(());
+                    };
+                    if let Err(e) = social.queue_automatic(post_id).await {
+                        tracing::warn!(post_id = %post_id, "Failed to queue social shares: {}", e);
+                    }
+                    Ok(())
+                }
+            },
+        ));
+    }
+}
+
+/// Periodic dispatch of due social shares
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DispatchSocialSharesJob {}
+
+impl JobPayload for DispatchSocialSharesJob {
+    fn job_type() -> &'static str {
+        "dispatch_social_shares"
+    }
+
+    // Shares retry on their own schedule; a failed run is picked up by the
+    // next one
+    fn max_attempts() -> u32 {
+        1
+    }
+}
+
+/// Handler for [`DispatchSocialSharesJob`]
+#[derive(Clone)]
+pub struct DispatchSocialSharesHandler {
+    social: Arc<SocialService>,
+}
+
+impl DispatchSocialSharesHandler {
+    pub fn new(social: Arc<SocialService>) -> Self {
+        Self { social }
+    }
+}
+
+#[async_trait]
+impl JobHandler for DispatchSocialSharesHandler {
+    type Payload = DispatchSocialSharesJob;
+
+    async fn handle(&self, _payload: Self::Payload) -> Result<()> {
+        let report = self.social.dispatch_due().await?;
+        if report != DispatchReport::default() {
+            tracing::info!(
+                queued = report.queued,
+                sent = report.sent,
+                failed = report.failed,
+                retrying = report.retrying,
+                "Social shares dispatched"
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context() -> ShareContext {
+        ShareContext {
+            title: "Shipping RustPress 2.0".to_string(),
+            url: "https://example.com/post/shipping-rustpress-2-0".to_string(),
+            excerpt: "word ".repeat(200).trim().to_string(),
+            author: "Jane".to_string(),
+            site: "Example".to_string(),
+            hashtags: hashtags(["rust lang", "CMS", "rust-lang", "!!"]),
+        }
+    }
+
+    #[test]
+    fn test_render_message_fits_network() {
+        let context = context();
+        assert_eq!(context.hashtags, vec!["#RustLang", "#CMS"]);
+
+        let x = render_message(SocialNetwork::X, "{title} {url} {hashtags}", &context);
+        assert_eq!(
+            x,
+            "Shipping RustPress 2.0 https://example.com/post/shipping-rustpress-2-0 #RustLang #CMS"
+        );
+
+        let long = render_message(SocialNetwork::X, "{title}\n\n{excerpt}\n\n{url}", &context);
+        assert!(message_length(SocialNetwork::X, &long) <= 280, "{}", long);
+        assert!(long.contains("…\n\nhttps://example.com/post/"), "{}", long);
+
+        // Links count as 23 characters on X, whatever their length
+        let url = format!("https://example.com/{}", "a".repeat(100));
+        assert_eq!(message_length(SocialNetwork::X, &format!("hi {}", url)), 26);
+        assert_eq!(message_length(SocialNetwork::Linkedin, &url), 120);
+
+        // Empty placeholders leave no blank lines or double spaces behind
+        let sparse = ShareContext {
+            excerpt: String::new(),
+            hashtags: Vec::new(),
+            ..context
+        };
+        assert_eq!(
+            render_message(
+                SocialNetwork::Mastodon,
+                "{title}  \n\n{excerpt}\n\n{url} {hashtags}",
+                &sparse
+            ),
+            "Shipping RustPress 2.0\n\nhttps://example.com/post/shipping-rustpress-2-0"
+        );
+    }
+
+    #[test]
+    fn test_config_validation_and_tokens() {
+        let account = |id: &str, network| SocialAccount {
+            id: id.to_string(),
+            network,
+            name: String::new(),
+            enabled: true,
+            auto_post: true,
+            auto_post_since: None,
+            delay_minutes: 0,
+            template: None,
+            instance_url: Some("https://mastodon.example".to_string()),
+            target: Some("urn:li:person:abc".to_string()),
+            access_token: "secret".to_string(),
+        };
+        let mut config = SocialConfig {
+            accounts: vec![
+                account("masto", SocialNetwork::Mastodon),
+                account("li", SocialNetwork::Linkedin),
+            ],
+            ..Default::default()
+        };
+        config.validate().unwrap();
+
+        config.accounts[0].template = Some("{title} {link}".to_string());
+        assert!(config.validate().is_err());
+        config.accounts[0].template = None;
+        config.accounts[1].network = SocialNetwork::Facebook;
+        assert!(config.validate().is_err());
+        config.accounts[1].network = SocialNetwork::Linkedin;
+
+        // Masked tokens are kept, and auto-posting keeps its start time
+        let now = Utc::now();
+        let mut stored = config.clone();
+        stored.merge(&SocialConfig::default(), now - Duration::days(1));
+        let mut update = stored.masked();
+        assert_eq!(update.accounts[0].access_token, MASKED_SECRET);
+        update.accounts[1].auto_post = false;
+        update.merge(&stored, now);
+        assert_eq!(update.accounts[0].access_token, "secret");
+        assert_eq!(
+            update.accounts[0].auto_post_since,
+            Some(now - Duration::days(1))
+        );
+        assert_eq!(update.accounts[1].auto_post_since, None);
+
+        // Pointing an account elsewhere drops the stored token
+        let mut moved = stored.masked();
+        moved.accounts[0].instance_url = Some("https://attacker.example".to_string());
+        moved.accounts[1].network = SocialNetwork::Facebook;
+        moved.merge(&stored, now);
+        assert!(moved.accounts[0].access_token.is_empty());
+        assert!(moved.accounts[1].access_token.is_empty());
+        assert!(moved.validate().is_err());
+    }
+
+    #[test]
+    fn test_card_rendering() {
+        let (lines, cut) = wrap_text("one two three four five six", 9, 2);
+        assert_eq!(lines, vec!["one two", "three…"]);
+        assert!(cut);
+        assert_eq!(wrap_text("abcdefghij", 4, 5).0, vec!["abcd", "efgh", "ij"]);
+
+        let settings = CardSettings::default();
+        let card = CardContext::new(
+            &settings,
+            "Site & Co",
+            "<script>alert(1)</script> title",
+            None,
+            Some("Jane"),
+            None,
+            None,
+        );
+        assert_eq!(card.byline, "Jane");
+        let svg = render_card(&settings, &card).unwrap();
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains("Site &amp; Co"));
+        assert!(!svg.contains("<script>"));
+        assert!(!svg.contains("<image"));
+        assert!(!svg.contains("letter-spacing"));
+
+        // Categories take their configured colour, others the accent
+        let mut settings = CardSettings::default();
+        settings
+            .category_colors
+            .insert("news".to_string(), "#22c55e".to_string());
+        let card = CardContext::new(&settings, "Site", "Title", None, None, None, None)
+            .with_category(&settings, "news", "News")
+            .with_logo(Some("https://example.com/logo.png".to_string()));
+        let svg = render_card(&settings, &card).unwrap();
+        assert!(svg.contains(">NEWS</text>"));
+        assert!(svg.contains("fill=\"#22c55e\""));
+        assert!(svg.contains("logo.png\" x=\"1024\""));
+        let card = CardContext::new(&settings, "Site", "Title", None, None, None, None)
+            .with_category(&settings, "other", "Other");
+        assert_eq!(card.category_color, settings.accent);
+
+        let mut config = SocialConfig::default();
+        config.card.template = Some("<svg>{{ missing }}</svg>".to_string());
+        assert!(config.validate().is_err());
+        config.card.template = Some("<svg><text>{{ title }}</text></svg>".to_string());
+        config.validate().unwrap();
+        config.card.logo_url = Some("javascript:alert(1)".to_string());
+        assert!(config.validate().is_err());
+        config.card.logo_url = Some("/uploads/logo.png".to_string());
+        config
+            .card
+            .category_colors
+            .insert("news".to_string(), "green".to_string());
+        assert!(config.validate().is_err());
+    }
+}
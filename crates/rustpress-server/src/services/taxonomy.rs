@@ -0,0 +1,1309 @@
+//! Taxonomies
+//!
+//! Categories, tags and custom taxonomies share one registry:
+//!
+//! - `category` and `post_tag` are built in. Custom taxonomies are
+//!   registered at runtime, and public ones get term archives at
+//!   `/{route}/{term}`, where the route is the rewrite slug or the slug
+//! - terms of hierarchical taxonomies nest; a term archive lists the posts
+//!   of its descendants too
+//! - terms carry JSON meta and can be merged into another term or split
+//!   into several. The archive of a merged or renamed term redirects to
+//!   its new one
+//! - posts are re-assigned in bulk by adding and removing terms on a set
+//!   of posts at once. Term counts only include published posts
+
+use chrono::{DateTime, Utc};
+use rustpress_core::error::{Error, Result};
+use rustpress_core::tenant::site_filter;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::types::Json;
+use sqlx::{FromRow, PgPool, Postgres, Transaction};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use super::cache_policy::SurrogateKeys;
+use super::redirects::RedirectService;
+
+/// Built-in taxonomy of categories
+pub const CATEGORY_TAXONOMY: &str = "category";
+
+/// Built-in taxonomy of tags
+pub const TAG_TAXONOMY: &str = "post_tag";
+
+/// First path segments taken by other public routes, which custom
+/// taxonomies cannot use for their archives
+const RESERVED_ROUTES: &[&str] = &[
+    "admin",
+    "api",
+    "author",
+    "avatar",
+    "blog",
+    "category",
+    "event",
+    "events",
+    "feed",
+    "health",
+    "metrics",
+    "og-image",
+    "page",
+    "pagebuilder",
+    "plugins",
+    "podcast",
+    "post",
+    "search",
+    "social-card",
+    "tag",
+    "themes",
+];
+
+/// Longest taxonomy slug
+const MAX_TAXONOMY_SLUG: usize = 32;
+
+/// Longest term slug
+const MAX_TERM_SLUG: usize = 200;
+
+/// Posts one bulk re-assignment may touch
+const MAX_REASSIGN_POSTS: usize = 1_000;
+
+/// Terms one split may produce
+const MAX_SPLIT_TERMS: usize = 20;
+
+/// A registered taxonomy
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct Taxonomy {
+    pub id: Uuid,
+    pub slug: String,
+    pub name: String,
+    pub singular_name: Option<String>,
+    pub description: Option<String>,
+    pub hierarchical: bool,
+    /// Public taxonomies have term archives
+    pub public: bool,
+    /// First segment of term archive URLs; `None` uses the slug
+    pub rewrite_slug: Option<String>,
+    /// Post types whose posts can carry terms of the taxonomy
+    pub post_types: Json<Vec<String>>,
+    /// Built-in taxonomies cannot be deleted or moved
+    pub is_system: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+const TAXONOMY_COLUMNS: &str = "id, slug, name, singular_name, description, hierarchical, public, \
+     rewrite_slug, post_types, is_system, created_at, updated_at";
+
+impl Taxonomy {
+    /// First segment of term archive URLs
+    pub fn route(&self) -> &str {
+        self.rewrite_slug.as_deref().unwrap_or(&self.slug)
+    }
+
+    /// Archive path of one of its terms
+    pub fn term_path(&self, term_slug: &str) -> String {
+        format!("/{}/{}", self.route(), term_slug)
+    }
+
+    pub async fn load(pool: &PgPool, slug: &str) -> Result<Option<Self>> {
+        sqlx::query_as(&format!(
+            "SELECT {} FROM taxonomies WHERE slug = $1",
+            TAXONOMY_COLUMNS
+        ))
+        .bind(slug)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load taxonomy", e))
+    }
+
+    /// Public taxonomy whose term archives live under a path segment
+    pub async fn by_route(pool: &PgPool, route: &str) -> Result<Option<Self>> {
+        sqlx::query_as(&format!(
+            "SELECT {} FROM taxonomies WHERE COALESCE(rewrite_slug, slug) = $1 AND public",
+            TAXONOMY_COLUMNS
+        ))
+        .bind(route)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load taxonomy", e))
+    }
+}
+
+/// A taxonomy to register, or new settings for one. The slug of an
+/// existing taxonomy cannot change and is ignored on update.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TaxonomyInput {
+    #[serde(default)]
+    pub slug: String,
+    pub name: String,
+    pub singular_name: Option<String>,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub hierarchical: bool,
+    #[serde(default = "default_public")]
+    pub public: bool,
+    pub rewrite_slug: Option<String>,
+    #[serde(default = "default_post_types")]
+    pub post_types: Vec<String>,
+}
+
+fn default_public() -> bool {
+    true
+}
+
+fn default_post_types() -> Vec<String> {
+    vec!["post".to_string()]
+}
+
+impl TaxonomyInput {
+    /// Trim and check the input
+    fn normalize(mut self) -> Result<Self> {
+        self.slug = self.slug.trim().to_lowercase();
+        if !is_identifier(&self.slug, MAX_TAXONOMY_SLUG) {
+            return Err(Error::invalid_input(
+                "slug",
+                format!(
+                    "A taxonomy slug starts with a letter and has at most {} lowercase letters, \
+                     digits, '-' or '_'",
+                    MAX_TAXONOMY_SLUG
+                ),
+            ));
+        }
+
+        self.name = self.name.trim().to_string();
+        if self.name.is_empty() || self.name.chars().count() > 100 {
+            return Err(Error::invalid_input(
+                "name",
+                "A taxonomy needs a name of at most 100 characters",
+            ));
+        }
+        self.singular_name = non_empty(self.singular_name);
+        self.description = non_empty(self.description);
+
+        self.rewrite_slug = non_empty(self.rewrite_slug).map(|s| s.to_lowercase());
+        if let Some(ref rewrite) = self.rewrite_slug {
+            if !is_identifier(rewrite, 64) || rewrite.contains('_') {
+                return Err(Error::invalid_input(
+                    "rewrite_slug",
+                    "A rewrite slug starts with a letter and has only lowercase letters, \
+                     digits and '-'",
+                ));
+            }
+        }
+
+        let mut post_types = BTreeSet::new();
+        for post_type in &self.post_types {
+            let post_type = post_type.trim();
+            if !is_identifier(post_type, 50) {
+                return Err(Error::invalid_input(
+                    "post_types",
+                    format!("'{}' is not a post type", post_type),
+                ));
+            }
+            post_types.insert(post_type.to_string());
+        }
+        if post_types.is_empty() {
+            return Err(Error::invalid_input(
+                "post_types",
+                "A taxonomy applies to at least one post type",
+            ));
+        }
+        self.post_types = post_types.into_iter().collect();
+        Ok(self)
+    }
+
+    fn route(&self) -> &str {
+        self.rewrite_slug.as_deref().unwrap_or(&self.slug)
+    }
+}
+
+/// A term of a taxonomy
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct Term {
+    pub id: Uuid,
+    pub taxonomy_id: Uuid,
+    /// Slug of the taxonomy
+    pub taxonomy: String,
+    pub parent_id: Option<Uuid>,
+    pub name: String,
+    pub slug: String,
+    pub description: Option<String>,
+    pub term_order: i32,
+    /// Published posts carrying the term
+    pub count: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+const TERM_COLUMNS: &str = "t.id, t.taxonomy_id, tx.slug AS taxonomy, t.parent_id, t.name, \
+     t.slug, t.description, t.term_order, t.count, t.created_at, t.updated_at";
+
+impl Term {
+    async fn load<'e, E>(executor: E, id: Uuid) -> Result<Option<Self>>
+    where
+        E: sqlx::PgExecutor<'e>,
+    {
+        sqlx::query_as(&format!(
+            "SELECT {} FROM terms t JOIN taxonomies tx ON tx.id = t.taxonomy_id WHERE t.id = $1",
+            TERM_COLUMNS
+        ))
+        .bind(id)
+        .fetch_optional(executor)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load term", e))
+    }
+}
+
+/// Surrogate keys of the pages listing any of the terms
+pub fn term_keys<'a>(terms: impl IntoIterator<Item = &'a Term>) -> SurrogateKeys {
+    terms.into_iter().fold(SurrogateKeys::new(), |keys, term| {
+        keys.term(&term.taxonomy, &term.id.to_string())
+    })
+}
+
+/// A term with its children, for tree views of hierarchical taxonomies
+#[derive(Debug, Clone, Serialize)]
+pub struct TermNode {
+    #[serde(flatten)]
+    pub term: Term,
+    pub children: Vec<TermNode>,
+}
+
+/// Nest terms under their parents, keeping their order. Terms whose
+/// parent is not in the list become roots.
+pub fn build_tree(terms: Vec<Term>) -> Vec<TermNode> {
+    let ids: HashSet<Uuid> = terms.iter().map(|t| t.id).collect();
+    let mut children: HashMap<Option<Uuid>, Vec<Term>> = HashMap::new();
+    for term in terms {
+        let parent = term.parent_id.filter(|id| ids.contains(id));
+        children.entry(parent).or_default().push(term);
+    }
+
+    fn attach(
+        parent: Option<Uuid>,
+        children: &mut HashMap<Option<Uuid>, Vec<Term>>,
+    ) -> Vec<TermNode> {
+        children
+            .remove(&parent)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|term| {
+                let nested = attach(Some(term.id), children);
+                TermNode {
+                    term,
+                    children: nested,
+                }
+            })
+            .collect()
+    }
+    attach(None, &mut children)
+}
+
+/// Whether `ancestor` is `term` or one of its ancestors, given each
+/// term's parent
+fn is_ancestor(ancestor: Uuid, term: Uuid, parents: &HashMap<Uuid, Option<Uuid>>) -> bool {
+    let mut current = Some(term);
+    let mut seen = HashSet::new();
+    while let Some(id) = current {
+        if id == ancestor {
+            return true;
+        }
+        if !seen.insert(id) {
+            return false;
+        }
+        current = parents.get(&id).copied().flatten();
+    }
+    false
+}
+
+/// A term to create, or new values for one
+#[derive(Debug, Clone, Deserialize)]
+pub struct TermInput {
+    pub name: String,
+    /// Derived from the name when absent
+    pub slug: Option<String>,
+    pub description: Option<String>,
+    pub parent_id: Option<Uuid>,
+    #[serde(default)]
+    pub term_order: i32,
+}
+
+impl TermInput {
+    fn normalize(mut self) -> Result<Self> {
+        self.name = self.name.trim().to_string();
+        if self.name.is_empty() || self.name.chars().count() > 255 {
+            return Err(Error::invalid_input(
+                "name",
+                "A term needs a name of at most 255 characters",
+            ));
+        }
+        let slug = term_slug(self.slug.as_deref().unwrap_or(&self.name));
+        if slug.is_empty() {
+            return Err(Error::invalid_input(
+                "slug",
+                "The term slug has no letters or digits",
+            ));
+        }
+        self.slug = Some(slug);
+        self.description = non_empty(self.description);
+        Ok(self)
+    }
+
+    fn slug(&self) -> &str {
+        self.slug.as_deref().unwrap_or_default()
+    }
+}
+
+/// Listing filters for the terms of a taxonomy
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TermQuery {
+    /// Return every term nested under its parent instead of a page
+    #[serde(default)]
+    pub tree: bool,
+    pub search: Option<String>,
+}
+
+/// Move the posts of a term to new terms
+#[derive(Debug, Clone, Deserialize)]
+pub struct SplitInput {
+    pub terms: Vec<SplitTerm>,
+    /// Delete the source term once split; otherwise it keeps the posts
+    /// not moved to a new term
+    #[serde(default)]
+    pub delete_source: bool,
+}
+
+/// A term created by a split and the posts moved to it
+#[derive(Debug, Clone, Deserialize)]
+pub struct SplitTerm {
+    pub name: String,
+    pub slug: Option<String>,
+    #[serde(default)]
+    pub post_ids: Vec<Uuid>,
+}
+
+/// Add and remove terms on many posts at once
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReassignInput {
+    pub post_ids: Vec<Uuid>,
+    #[serde(default)]
+    pub add: Vec<Uuid>,
+    #[serde(default)]
+    pub remove: Vec<Uuid>,
+}
+
+/// Outcome of a bulk re-assignment
+#[derive(Debug, Clone, Serialize)]
+pub struct ReassignReport {
+    pub posts: usize,
+    /// Term assignments made
+    pub added: u64,
+    /// Term assignments removed
+    pub removed: u64,
+    /// The added and removed terms, with their new counts
+    pub terms: Vec<Term>,
+}
+
+/// Taxonomy registry, terms and term assignments
+pub struct TaxonomyService {
+    pool: PgPool,
+    redirects: Arc<RedirectService>,
+}
+
+impl TaxonomyService {
+    pub fn new(pool: PgPool, redirects: Arc<RedirectService>) -> Self {
+        Self { pool, redirects }
+    }
+
+    /// Registered taxonomies, built-in ones first
+    pub async fn taxonomies(&self) -> Result<Vec<Taxonomy>> {
+        sqlx::query_as(&format!(
+            "SELECT {} FROM taxonomies ORDER BY is_system DESC, name",
+            TAXONOMY_COLUMNS
+        ))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to list taxonomies", e))
+    }
+
+    pub async fn taxonomy(&self, slug: &str) -> Result<Taxonomy> {
+        Taxonomy::load(&self.pool, slug)
+            .await?
+            .ok_or_else(|| Error::not_found("Taxonomy", slug))
+    }
+
+    /// Register a custom taxonomy
+    pub async fn register(&self, input: TaxonomyInput) -> Result<Taxonomy> {
+        let input = input.normalize()?;
+        if RESERVED_ROUTES.contains(&input.route()) {
+            return Err(Error::invalid_input(
+                "rewrite_slug",
+                format!("'/{}' is used by another part of the site", input.route()),
+            ));
+        }
+        self.check_route(input.route(), None).await?;
+
+        sqlx::query_as(&format!(
+            "INSERT INTO taxonomies (id, slug, name, singular_name, description, hierarchical, \
+             public, rewrite_slug, post_types) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) \
+             RETURNING {}",
+            TAXONOMY_COLUMNS
+        ))
+        .bind(Uuid::now_v7())
+        .bind(&input.slug)
+        .bind(&input.name)
+        .bind(&input.singular_name)
+        .bind(&input.description)
+        .bind(input.hierarchical)
+        .bind(input.public)
+        .bind(&input.rewrite_slug)
+        .bind(Json(&input.post_types))
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| {
+            if unique_violation(&e) {
+                Error::Duplicate {
+                    entity_type: "Taxonomy".to_string(),
+                    field: "slug".to_string(),
+                }
+            } else {
+                Error::database_with_source("Failed to register taxonomy", e)
+            }
+        })
+    }
+
+    /// Change a taxonomy's settings. Built-in taxonomies keep their
+    /// archive URLs and structure; a taxonomy that stops being
+    /// hierarchical has its terms flattened.
+    pub async fn update_taxonomy(&self, slug: &str, input: TaxonomyInput) -> Result<Taxonomy> {
+        let current = self.taxonomy(slug).await?;
+        let input = TaxonomyInput {
+            slug: current.slug.clone(),
+            ..input
+        }
+        .normalize()?;
+        if current.is_system
+            && (input.route() != current.route()
+                || input.hierarchical != current.hierarchical
+                || !input.public)
+        {
+            return Err(Error::validation(
+                "Built-in taxonomies keep their archive URL, hierarchy and visibility",
+            ));
+        }
+        if !current.is_system && RESERVED_ROUTES.contains(&input.route()) {
+            return Err(Error::invalid_input(
+                "rewrite_slug",
+                format!("'/{}' is used by another part of the site", input.route()),
+            ));
+        }
+        self.check_route(input.route(), Some(current.id)).await?;
+
+        let mut tx = self.begin().await?;
+        if current.hierarchical && !input.hierarchical {
+            sqlx::query("UPDATE terms SET parent_id = NULL WHERE taxonomy_id = $1")
+                .bind(current.id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| Error::database_with_source("Failed to flatten terms", e))?;
+        }
+        let taxonomy = sqlx::query_as(&format!(
+            "UPDATE taxonomies SET name = $2, singular_name = $3, description = $4, \
+             hierarchical = $5, public = $6, rewrite_slug = $7, post_types = $8, \
+             updated_at = NOW() WHERE id = $1 RETURNING {}",
+            TAXONOMY_COLUMNS
+        ))
+        .bind(current.id)
+        .bind(&input.name)
+        .bind(&input.singular_name)
+        .bind(&input.description)
+        .bind(input.hierarchical)
+        .bind(input.public)
+        .bind(&input.rewrite_slug)
+        .bind(Json(&input.post_types))
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to update taxonomy", e))?;
+        commit(tx).await?;
+        Ok(taxonomy)
+    }
+
+    /// Delete a custom taxonomy with its terms and their assignments
+    pub async fn delete_taxonomy(&self, slug: &str) -> Result<()> {
+        let taxonomy = self.taxonomy(slug).await?;
+        if taxonomy.is_system {
+            return Err(Error::validation("Built-in taxonomies cannot be deleted"));
+        }
+        sqlx::query("DELETE FROM taxonomies WHERE id = $1")
+            .bind(taxonomy.id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to delete taxonomy", e))?;
+        Ok(())
+    }
+
+    /// A page of a taxonomy's terms, by order then name
+    pub async fn terms(
+        &self,
+        taxonomy: &str,
+        query: &TermQuery,
+        page: u32,
+        per_page: u32,
+    ) -> Result<(Vec<Term>, u64)> {
+        let taxonomy = self.taxonomy(taxonomy).await?;
+        let search = query
+            .search
+            .as_deref()
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(|s| format!("%{}%", s.replace('%', "\\%").replace('_', "\\_")));
+
+        let terms = sqlx::query_as(&format!(
+            "SELECT {} FROM terms t JOIN taxonomies tx ON tx.id = t.taxonomy_id \
+             WHERE t.taxonomy_id = $1 AND ($2::text IS NULL OR t.name ILIKE $2 OR t.slug ILIKE $2) \
+             ORDER BY t.term_order, t.name LIMIT $3 OFFSET $4",
+            TERM_COLUMNS
+        ))
+        .bind(taxonomy.id)
+        .bind(&search)
+        .bind(i64::from(per_page))
+        .bind(i64::from(page.saturating_sub(1)) * i64::from(per_page))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to list terms", e))?;
+
+        let total: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM terms t \
+             WHERE t.taxonomy_id = $1 AND ($2::text IS NULL OR t.name ILIKE $2 OR t.slug ILIKE $2)",
+        )
+        .bind(taxonomy.id)
+        .bind(&search)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to count terms", e))?;
+        Ok((terms, total as u64))
+    }
+
+    /// Every term of a taxonomy, nested under its parent
+    pub async fn tree(&self, taxonomy: &str) -> Result<Vec<TermNode>> {
+        let taxonomy = self.taxonomy(taxonomy).await?;
+        let terms = sqlx::query_as(&format!(
+            "SELECT {} FROM terms t JOIN taxonomies tx ON tx.id = t.taxonomy_id \
+             WHERE t.taxonomy_id = $1 ORDER BY t.term_order, t.name",
+            TERM_COLUMNS
+        ))
+        .bind(taxonomy.id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to list terms", e))?;
+        Ok(build_tree(terms))
+    }
+
+    pub async fn term(&self, id: Uuid) -> Result<Term> {
+        Term::load(&self.pool, id)
+            .await?
+            .ok_or_else(|| Error::not_found("Term", id.to_string()))
+    }
+
+    pub async fn create_term(&self, taxonomy: &str, input: TermInput) -> Result<Term> {
+        let taxonomy = self.taxonomy(taxonomy).await?;
+        let input = input.normalize()?;
+        self.check_parent(&taxonomy, None, input.parent_id).await?;
+
+        let id = Uuid::now_v7();
+        sqlx::query(
+            "INSERT INTO terms (id, taxonomy_id, parent_id, name, slug, description, term_order) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7)",
+        )
+        .bind(id)
+        .bind(taxonomy.id)
+        .bind(input.parent_id)
+        .bind(&input.name)
+        .bind(input.slug())
+        .bind(&input.description)
+        .bind(input.term_order)
+        .execute(&self.pool)
+        .await
+        .map_err(save_error)?;
+        self.term(id).await
+    }
+
+    /// Rename or move a term. The old archive path of a public taxonomy's
+    /// term redirects to the new one when the slug changes.
+    pub async fn update_term(&self, id: Uuid, input: TermInput) -> Result<Term> {
+        let current = self.term(id).await?;
+        let taxonomy = self.taxonomy(&current.taxonomy).await?;
+        let input = input.normalize()?;
+        self.check_parent(&taxonomy, Some(id), input.parent_id)
+            .await?;
+
+        sqlx::query(
+            "UPDATE terms SET parent_id = $2, name = $3, slug = $4, description = $5, \
+             term_order = $6, updated_at = NOW() WHERE id = $1",
+        )
+        .bind(id)
+        .bind(input.parent_id)
+        .bind(&input.name)
+        .bind(input.slug())
+        .bind(&input.description)
+        .bind(input.term_order)
+        .execute(&self.pool)
+        .await
+        .map_err(save_error)?;
+
+        if taxonomy.public && current.slug != input.slug() {
+            self.redirects
+                .record_move(
+                    &taxonomy.term_path(&current.slug),
+                    &taxonomy.term_path(input.slug()),
+                )
+                .await?;
+        }
+        self.term(id).await
+    }
+
+    /// Delete a term; its children move up to its parent
+    pub async fn delete_term(&self, id: Uuid) -> Result<Term> {
+        let term = self.term(id).await?;
+        let mut tx = self.begin().await?;
+        sqlx::query("UPDATE terms SET parent_id = $2 WHERE parent_id = $1")
+            .bind(id)
+            .bind(term.parent_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to move child terms", e))?;
+        sqlx::query("DELETE FROM terms WHERE id = $1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to delete term", e))?;
+        commit(tx).await?;
+        Ok(term)
+    }
+
+    /// Meta of a term
+    pub async fn meta(&self, id: Uuid) -> Result<HashMap<String, Value>> {
+        self.term(id).await?;
+        let rows: Vec<(String, Value)> =
+            sqlx::query_as("SELECT meta_key, meta_value FROM term_meta WHERE term_id = $1")
+                .bind(id)
+                .fetch_all(&self.pool)
+                .await
+                .map_err(|e| Error::database_with_source("Failed to load term meta", e))?;
+        Ok(rows.into_iter().collect())
+    }
+
+    /// Set meta keys of a term; keys set to `null` are removed
+    pub async fn update_meta(
+        &self,
+        id: Uuid,
+        values: HashMap<String, Value>,
+    ) -> Result<HashMap<String, Value>> {
+        self.term(id).await?;
+        if let Some(key) = values
+            .keys()
+            .find(|key| key.trim().is_empty() || key.len() > 255)
+        {
+            return Err(Error::invalid_input(
+                "meta",
+                format!("'{}' is not a valid meta key", key),
+            ));
+        }
+
+        let mut tx = self.begin().await?;
+        for (key, value) in &values {
+            let query = if value.is_null() {
+                sqlx::query("DELETE FROM term_meta WHERE term_id = $1 AND meta_key = $2")
+            } else {
+                sqlx::query(
+                    "INSERT INTO term_meta (term_id, meta_key, meta_value) VALUES ($1, $2, $3) \
+                     ON CONFLICT (term_id, meta_key) DO UPDATE SET meta_value = EXCLUDED.meta_value",
+                )
+            };
+            query
+                .bind(id)
+                .bind(key)
+                .bind(value)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| Error::database_with_source("Failed to save term meta", e))?;
+        }
+        commit(tx).await?;
+        self.meta(id).await
+    }
+
+    /// Merge a term into another of the same taxonomy: its posts and
+    /// children move over, meta the target lacks is copied, and the source
+    /// is deleted. Returns the target.
+    pub async fn merge(&self, source_id: Uuid, target_id: Uuid) -> Result<Term> {
+        if source_id == target_id {
+            return Err(Error::invalid_input(
+                "into",
+                "A term cannot be merged into itself",
+            ));
+        }
+        let source = self.term(source_id).await?;
+        let target = self.term(target_id).await?;
+        if source.taxonomy_id != target.taxonomy_id {
+            return Err(Error::invalid_input(
+                "into",
+                "Terms can only be merged within one taxonomy",
+            ));
+        }
+        let taxonomy = self.taxonomy(&source.taxonomy).await?;
+
+        let mut tx = self.begin().await?;
+        sqlx::query(
+            "INSERT INTO term_relationships (object_id, object_type, term_id, term_order) \
+             SELECT object_id, object_type, $2, term_order FROM term_relationships \
+             WHERE term_id = $1 ON CONFLICT DO NOTHING",
+        )
+        .bind(source_id)
+        .bind(target_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to move term posts", e))?;
+
+        // A target nested under the source takes the source's place first,
+        // so handing it the source's children cannot create a cycle
+        let parents = parent_map(&mut *tx, source.taxonomy_id).await?;
+        if is_ancestor(source_id, target_id, &parents) {
+            sqlx::query("UPDATE terms SET parent_id = $2 WHERE id = $1")
+                .bind(target_id)
+                .bind(source.parent_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| Error::database_with_source("Failed to move term", e))?;
+        }
+        sqlx::query("UPDATE terms SET parent_id = $2 WHERE parent_id = $1 AND id <> $2")
+            .bind(source_id)
+            .bind(target_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to move child terms", e))?;
+
+        sqlx::query(
+            "INSERT INTO term_meta (term_id, meta_key, meta_value) \
+             SELECT $2, meta_key, meta_value FROM term_meta WHERE term_id = $1 \
+             ON CONFLICT DO NOTHING",
+        )
+        .bind(source_id)
+        .bind(target_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to copy term meta", e))?;
+
+        sqlx::query("DELETE FROM terms WHERE id = $1")
+            .bind(source_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to delete term", e))?;
+        recount(&mut tx, &[target_id]).await?;
+        commit(tx).await?;
+
+        if taxonomy.public {
+            self.redirects
+                .record_move(
+                    &taxonomy.term_path(&source.slug),
+                    &taxonomy.term_path(&target.slug),
+                )
+                .await?;
+        }
+        self.term(target_id).await
+    }
+
+    /// Split a term: create new terms next to it and move the listed posts
+    /// that carry it over to them. Returns the new terms.
+    pub async fn split(&self, source_id: Uuid, input: SplitInput) -> Result<Vec<Term>> {
+        if input.terms.is_empty() || input.terms.len() > MAX_SPLIT_TERMS {
+            return Err(Error::invalid_input(
+                "terms",
+                format!("A term splits into 1 to {} terms", MAX_SPLIT_TERMS),
+            ));
+        }
+        let source = self.term(source_id).await?;
+        let parts = input
+            .terms
+            .into_iter()
+            .map(|part| {
+                let term = TermInput {
+                    name: part.name,
+                    slug: part.slug,
+                    description: None,
+                    parent_id: source.parent_id,
+                    term_order: source.term_order,
+                }
+                .normalize()?;
+                Ok((term, part.post_ids))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut tx = self.begin().await?;
+        let mut ids = Vec::with_capacity(parts.len());
+        for (term, post_ids) in &parts {
+            let id = Uuid::now_v7();
+            sqlx::query(
+                "INSERT INTO terms (id, taxonomy_id, parent_id, name, slug, description, \
+                 term_order) VALUES ($1, $2, $3, $4, $5, $6, $7)",
+            )
+            .bind(id)
+            .bind(source.taxonomy_id)
+            .bind(term.parent_id)
+            .bind(&term.name)
+            .bind(term.slug())
+            .bind(&source.description)
+            .bind(term.term_order)
+            .execute(&mut *tx)
+            .await
+            .map_err(save_error)?;
+
+            sqlx::query(
+                "INSERT INTO term_meta (term_id, meta_key, meta_value) \
+                 SELECT $2, meta_key, meta_value FROM term_meta WHERE term_id = $1",
+            )
+            .bind(source_id)
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to copy term meta", e))?;
+
+            sqlx::query(
+                "INSERT INTO term_relationships (object_id, object_type, term_id, term_order) \
+                 SELECT object_id, object_type, $2, term_order FROM term_relationships \
+                 WHERE term_id = $1 AND object_type = 'post' AND object_id = ANY($3) \
+                 ON CONFLICT DO NOTHING",
+            )
+            .bind(source_id)
+            .bind(id)
+            .bind(post_ids)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to move term posts", e))?;
+            ids.push(id);
+        }
+
+        if input.delete_source {
+            sqlx::query("UPDATE terms SET parent_id = $2 WHERE parent_id = $1")
+                .bind(source_id)
+                .bind(source.parent_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| Error::database_with_source("Failed to move child terms", e))?;
+            sqlx::query("DELETE FROM terms WHERE id = $1")
+                .bind(source_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| Error::database_with_source("Failed to delete term", e))?;
+        } else {
+            let moved: Vec<Uuid> = parts
+                .iter()
+                .flat_map(|(_, post_ids)| post_ids.iter().copied())
+                .collect();
+            sqlx::query(
+                "DELETE FROM term_relationships \
+                 WHERE term_id = $1 AND object_type = 'post' AND object_id = ANY($2)",
+            )
+            .bind(source_id)
+            .bind(&moved)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to move term posts", e))?;
+            ids.push(source_id);
+        }
+        recount(&mut tx, &ids).await?;
+        commit(tx).await?;
+
+        let mut terms = Vec::with_capacity(ids.len());
+        for id in ids.into_iter().filter(|id| *id != source_id) {
+            terms.push(self.term(id).await?);
+        }
+        Ok(terms)
+    }
+
+    /// Add and remove terms on a set of posts
+    pub async fn reassign(&self, input: ReassignInput) -> Result<ReassignReport> {
+        let post_ids: Vec<Uuid> = dedup(&input.post_ids);
+        let add = dedup(&input.add);
+        let remove = dedup(&input.remove);
+        if post_ids.is_empty() || post_ids.len() > MAX_REASSIGN_POSTS {
+            return Err(Error::invalid_input(
+                "post_ids",
+                format!("Re-assign terms on 1 to {} posts", MAX_REASSIGN_POSTS),
+            ));
+        }
+        if add.is_empty() && remove.is_empty() {
+            return Err(Error::invalid_input("add", "Name terms to add or remove"));
+        }
+        if add.iter().any(|id| remove.contains(id)) {
+            return Err(Error::invalid_input(
+                "remove",
+                "A term cannot be added and removed at once",
+            ));
+        }
+
+        let found: i64 = sqlx::query_scalar(&format!(
+            "SELECT COUNT(*) FROM posts WHERE id = ANY($1) AND deleted_at IS NULL AND {}",
+            site_filter("site_id")
+        ))
+        .bind(&post_ids)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load posts", e))?;
+        if found as usize != post_ids.len() {
+            return Err(Error::invalid_input("post_ids", "Some posts do not exist"));
+        }
+        let terms: Vec<Uuid> = add.iter().chain(&remove).copied().collect();
+        let known: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM terms WHERE id = ANY($1)")
+            .bind(&terms)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to load terms", e))?;
+        if known as usize != terms.len() {
+            return Err(Error::invalid_input("add", "Some terms do not exist"));
+        }
+
+        // Terms only go on posts of the types their taxonomy applies to
+        let mismatch: Option<(String, String)> = sqlx::query_as(
+            "SELECT t.name, p.post_type FROM terms t \
+             JOIN taxonomies tx ON tx.id = t.taxonomy_id \
+             JOIN posts p ON p.id = ANY($2) \
+             WHERE t.id = ANY($1) AND NOT tx.post_types ? p.post_type LIMIT 1",
+        )
+        .bind(&add)
+        .bind(&post_ids)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to check post types", e))?;
+        if let Some((term, post_type)) = mismatch {
+            return Err(Error::invalid_input(
+                "add",
+                format!("'{}' does not apply to posts of type '{}'", term, post_type),
+            ));
+        }
+
+        let mut tx = self.begin().await?;
+        let added = sqlx::query(
+            "INSERT INTO term_relationships (object_id, object_type, term_id) \
+             SELECT p.id, 'post', t.id FROM unnest($1::uuid[]) AS p(id) \
+             CROSS JOIN unnest($2::uuid[]) AS t(id) ON CONFLICT DO NOTHING",
+        )
+        .bind(&post_ids)
+        .bind(&add)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to add terms", e))?
+        .rows_affected();
+        let removed = sqlx::query(
+            "DELETE FROM term_relationships \
+             WHERE object_type = 'post' AND object_id = ANY($1) AND term_id = ANY($2)",
+        )
+        .bind(&post_ids)
+        .bind(&remove)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to remove terms", e))?
+        .rows_affected();
+        recount(&mut tx, &terms).await?;
+        commit(tx).await?;
+
+        let mut affected = Vec::with_capacity(terms.len());
+        for id in terms {
+            affected.push(self.term(id).await?);
+        }
+        Ok(ReassignReport {
+            posts: post_ids.len(),
+            added,
+            removed,
+            terms: affected,
+        })
+    }
+
+    /// Reject a rewrite route another taxonomy already uses
+    async fn check_route(&self, route: &str, except: Option<Uuid>) -> Result<()> {
+        let taken: Option<Uuid> =
+            sqlx::query_scalar("SELECT id FROM taxonomies WHERE COALESCE(rewrite_slug, slug) = $1")
+                .bind(route)
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(|e| Error::database_with_source("Failed to check taxonomy route", e))?;
+        match taken {
+            Some(id) if Some(id) != except => Err(Error::Duplicate {
+                entity_type: "Taxonomy".to_string(),
+                field: "rewrite_slug".to_string(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// A parent must be a term of the same hierarchical taxonomy, and not
+    /// the term itself or one of its descendants
+    async fn check_parent(
+        &self,
+        taxonomy: &Taxonomy,
+        term_id: Option<Uuid>,
+        parent_id: Option<Uuid>,
+    ) -> Result<()> {
+        let Some(parent_id) = parent_id else {
+            return Ok(());
+        };
+        if !taxonomy.hierarchical {
+            return Err(Error::invalid_input(
+                "parent_id",
+                format!("Terms of '{}' cannot be nested", taxonomy.name),
+            ));
+        }
+        let parent = self.term(parent_id).await?;
+        if parent.taxonomy_id != taxonomy.id {
+            return Err(Error::invalid_input(
+                "parent_id",
+                "The parent belongs to another taxonomy",
+            ));
+        }
+        if let Some(term_id) = term_id {
+            let parents = parent_map(&self.pool, taxonomy.id).await?;
+            if is_ancestor(term_id, parent_id, &parents) {
+                return Err(Error::invalid_input(
+                    "parent_id",
+                    "A term cannot be nested under itself or its descendants",
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    async fn begin(&self) -> Result<Transaction<'static, Postgres>> {
+        self.pool
+            .begin()
+            .await
+            .map_err(|e| Error::database_with_source("Failed to start transaction", e))
+    }
+}
+
+/// Term ids with the ids of all their descendants, as `(term, descendant)`
+/// pairs; every term is paired with itself
+pub async fn descendant_ids(pool: &PgPool, term_ids: &[Uuid]) -> Result<Vec<(Uuid, Uuid)>> {
+    sqlx::query_as(
+        "WITH RECURSIVE tree (root, id) AS ( \
+             SELECT id, id FROM terms WHERE id = ANY($1) \
+             UNION \
+             SELECT tree.root, t.id FROM terms t JOIN tree ON t.parent_id = tree.id \
+         ) SELECT root, id FROM tree",
+    )
+    .bind(term_ids)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| Error::database_with_source("Failed to load child terms", e))
+}
+
+/// Parent of each term of a taxonomy
+async fn parent_map<'e, E>(executor: E, taxonomy_id: Uuid) -> Result<HashMap<Uuid, Option<Uuid>>>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    let rows: Vec<(Uuid, Option<Uuid>)> =
+        sqlx::query_as("SELECT id, parent_id FROM terms WHERE taxonomy_id = $1")
+            .bind(taxonomy_id)
+            .fetch_all(executor)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to load terms", e))?;
+    Ok(rows.into_iter().collect())
+}
+
+/// Refresh the published post counts of terms
+async fn recount(tx: &mut Transaction<'static, Postgres>, term_ids: &[Uuid]) -> Result<()> {
+    sqlx::query(
+        "UPDATE terms t SET count = ( \
+             SELECT COUNT(*) FROM term_relationships tr JOIN posts p ON p.id = tr.object_id \
+             WHERE tr.term_id = t.id AND tr.object_type = 'post' \
+             AND p.status = 'published' AND p.deleted_at IS NULL \
+         ) WHERE t.id = ANY($1)",
+    )
+    .bind(term_ids)
+    .execute(&mut **tx)
+    .await
+    .map_err(|e| Error::database_with_source("Failed to count term posts", e))?;
+    Ok(())
+}
+
+async fn commit(tx: Transaction<'static, Postgres>) -> Result<()> {
+    tx.commit()
+        .await
+        .map_err(|e| Error::database_with_source("Failed to commit transaction", e))
+}
+
+fn unique_violation(e: &sqlx::Error) -> bool {
+    matches!(e, sqlx::Error::Database(db) if db.code().as_deref() == Some("23505"))
+}
+
+fn save_error(e: sqlx::Error) -> Error {
+    if unique_violation(&e) {
+        Error::invalid_input("slug", "The taxonomy already has a term with this slug")
+    } else {
+        Error::database_with_source("Failed to save term", e)
+    }
+}
+
+/// Starts with a lowercase letter, then lowercase letters, digits, `-` or `_`
+fn is_identifier(value: &str, max_len: usize) -> bool {
+    value.len() <= max_len
+        && value.starts_with(|c: char| c.is_ascii_lowercase())
+        && value
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+}
+
+fn term_slug(value: &str) -> String {
+    slugify::slugify(value, "", "-", Some(MAX_TERM_SLUG))
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+fn dedup(ids: &[Uuid]) -> Vec<Uuid> {
+    let mut seen = HashSet::new();
+    ids.iter().copied().filter(|id| seen.insert(*id)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn term(id: u128, parent: Option<u128>, name: &str) -> Term {
+        Term {
+            id: Uuid::from_u128(id),
+            taxonomy_id: Uuid::nil(),
+            taxonomy: CATEGORY_TAXONOMY.to_string(),
+            parent_id: parent.map(Uuid::from_u128),
+            name: name.to_string(),
+            slug: term_slug(name),
+            description: None,
+            term_order: 0,
+            count: 0,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_build_tree() {
+        let tree = build_tree(vec![
+            term(1, None, "News"),
+            term(2, Some(1), "World"),
+            term(3, Some(2), "Europe"),
+            term(4, None, "Sport"),
+            term(5, Some(1), "Local"),
+            // Parent outside the list
+            term(6, Some(99), "Orphan"),
+        ]);
+
+        let names: Vec<&str> = tree.iter().map(|n| n.term.name.as_str()).collect();
+        assert_eq!(names, ["News", "Sport", "Orphan"]);
+        let news = &tree[0];
+        let children: Vec<&str> = news.children.iter().map(|n| n.term.name.as_str()).collect();
+        assert_eq!(children, ["World", "Local"]);
+        assert_eq!(news.children[0].children[0].term.slug, "europe");
+
+        let json = serde_json::to_value(&tree[1]).unwrap();
+        assert_eq!(json["name"], "Sport");
+        assert_eq!(json["children"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn test_is_ancestor() {
+        let id = Uuid::from_u128;
+        let parents: HashMap<Uuid, Option<Uuid>> = [
+            (id(1), None),
+            (id(2), Some(id(1))),
+            (id(3), Some(id(2))),
+            (id(4), None),
+        ]
+        .into_iter()
+        .collect();
+
+        assert!(is_ancestor(id(1), id(3), &parents));
+        assert!(is_ancestor(id(3), id(3), &parents));
+        assert!(!is_ancestor(id(3), id(1), &parents));
+        assert!(!is_ancestor(id(4), id(3), &parents));
+
+        // Corrupt data with a loop still terminates
+        let looped: HashMap<Uuid, Option<Uuid>> = [(id(1), Some(id(2))), (id(2), Some(id(1)))]
+            .into_iter()
+            .collect();
+        assert!(!is_ancestor(id(3), id(1), &looped));
+    }
+
+    #[test]
+    fn test_taxonomy_input() {
+        let input: TaxonomyInput = serde_json::from_value(serde_json::json!({
+            "slug": " Genre ",
+            "name": " Genres ",
+            "rewrite_slug": "",
+            "post_types": ["post", "episode", "post"],
+        }))
+        .unwrap();
+        let input = input.normalize().unwrap();
+        assert_eq!(input.slug, "genre");
+        assert_eq!(input.name, "Genres");
+        assert_eq!(input.rewrite_slug, None);
+        assert_eq!(input.route(), "genre");
+        assert_eq!(input.post_types, ["episode", "post"]);
+        assert!(input.public);
+
+        let invalid = |value: serde_json::Value| {
+            serde_json::from_value::<TaxonomyInput>(value)
+                .unwrap()
+                .normalize()
+                .is_err()
+        };
+        assert!(invalid(
+            serde_json::json!({"slug": "2024", "name": "Years"})
+        ));
+        assert!(invalid(serde_json::json!({"slug": "genre", "name": " "})));
+        assert!(invalid(
+            serde_json::json!({"slug": "genre", "name": "Genres", "rewrite_slug": "by_genre"})
+        ));
+        assert!(invalid(
+            serde_json::json!({"slug": "genre", "name": "Genres", "post_types": []})
+        ));
+    }
+
+    #[test]
+    fn test_term_input() {
+        let input = TermInput {
+            name: "  Rust & WebAssembly ".to_string(),
+            slug: None,
+            description: Some(" ".to_string()),
+            parent_id: None,
+            term_order: 0,
+        }
+        .normalize()
+        .unwrap();
+        assert_eq!(input.name, "Rust & WebAssembly");
+        assert_eq!(input.slug(), "rust-webassembly");
+        assert_eq!(input.description, None);
+
+        let input = TermInput {
+            name: "Café".to_string(),
+            slug: Some("Caf\u{e9} Cr\u{e8}me".to_string()),
+            description: None,
+            parent_id: None,
+            term_order: 0,
+        }
+        .normalize()
+        .unwrap();
+        assert_eq!(input.slug(), "cafe-creme");
+
+        let input = TermInput {
+            name: "!!!".to_string(),
+            slug: None,
+            description: None,
+            parent_id: None,
+            term_order: 0,
+        };
+        assert!(input.normalize().is_err());
+    }
+}
@@ -8,15 +8,18 @@
 //! - Theme installation from ZIP files
 //! - Theme activation/deactivation
 //! - Theme settings management
-//! - Menu/widget assignments per theme
+//! - Menu assignments per theme
 //! - Theme preview sessions
 
 use chrono::{DateTime, Duration, Utc};
+use rustpress_core::compat::{CompatProfile, CompatReport, Platform};
 use rustpress_core::error::{Error, Result};
+use rustpress_core::tenant::current_site;
 use rustpress_database::repository::themes::{ThemeRepository, ThemeRow};
-use rustpress_themes::manager::{RegisteredTheme, ThemeManager};
+use rustpress_themes::manager::{RegisteredTheme, ThemeManager, ThemeManagerError};
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
+use std::collections::HashMap;
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -143,6 +146,12 @@ impl ThemeService {
         }
     }
 
+    /// Site the service manages themes for: its own, or else the site
+    /// the current request serves
+    pub fn site_id(&self) -> Option<Uuid> {
+        self.site_id.or_else(current_site)
+    }
+
     /// Get the themes directory path
     pub fn themes_dir(&self) -> &Path {
         &self.themes_dir
@@ -151,7 +160,7 @@ impl ThemeService {
     /// Get the theme repository with site context
     fn repo(&self) -> ThemeRepository {
         let repo = ThemeRepository::new(self.pool.clone());
-        if let Some(site_id) = self.site_id {
+        if let Some(site_id) = self.site_id() {
             repo.with_site(site_id)
         } else {
             repo
@@ -225,7 +234,7 @@ impl ThemeService {
         // Build theme row
         let theme_row = ThemeRow {
             id: existing.as_ref().map(|e| e.id).unwrap_or_else(Uuid::now_v7),
-            site_id: self.site_id,
+            site_id: self.site_id(),
             theme_id: theme_id.to_string(),
             name: registered.manifest.theme.name.clone(),
             description: Some(registered.manifest.theme.description.clone()),
@@ -269,13 +278,22 @@ impl ThemeService {
                 }),
             widget_areas: extra_meta
                 .as_ref()
-                .and_then(|m| m.get("widget_areas"))
+                .and_then(|m| {
+                    ["widget_areas", "widgetAreas", "sidebars"]
+                        .iter()
+                        .find_map(|key| m.get(*key))
+                })
                 .cloned()
+                .or_else(|| {
+                    let declared = &registered.manifest.layout.widget_areas;
+                    (!declared.is_empty()).then(|| serde_json::json!(declared))
+                })
                 .unwrap_or_else(|| {
                     serde_json::json!({
                         "sidebar": "Main Sidebar",
                         "footer-1": "Footer Column 1",
-                        "footer-2": "Footer Column 2"
+                        "footer-2": "Footer Column 2",
+                        "footer-3": "Footer Column 3"
                     })
                 }),
             customizer_schema: extra_meta
@@ -337,7 +355,13 @@ impl ThemeService {
         self.file_manager
             .activate(theme_id)
             .await
-            .map_err(|e| Error::internal(format!("Failed to activate theme: {}", e)))?;
+            .map_err(|e| match e {
+                ThemeManagerError::NotAllowed(id) => {
+                    Error::extension_not_allowed("Theme", id, "this network")
+                }
+                ThemeManagerError::Incompatible(report) => report.to_error(),
+                e => Error::internal(format!("Failed to activate theme: {}", e)),
+            })?;
 
         // Activate in database (trigger will deactivate others)
         let row = self.repo().activate(theme_id).await?;
@@ -345,6 +369,28 @@ impl ThemeService {
         Ok(ThemeInfo::from(row))
     }
 
+    /// Check a theme against the platform and the given active extensions
+    pub fn check_compatibility(
+        &self,
+        theme_id: &str,
+        active: &[CompatProfile],
+    ) -> Result<CompatReport> {
+        self.file_manager
+            .check_compatibility(theme_id, active)
+            .map_err(|_| Error::not_found("Theme", theme_id))
+    }
+
+    /// Check every installed theme against the platform and the given
+    /// active extensions
+    pub fn audit_compatibility(&self, active: &[CompatProfile]) -> Vec<CompatReport> {
+        self.file_manager.audit_compatibility(active)
+    }
+
+    /// Set what this installation provides, checked on activation
+    pub fn set_platform(&self, platform: Platform) {
+        self.file_manager.set_platform(platform);
+    }
+
     /// Delete a theme
     pub async fn delete_theme(&self, theme_id: &str) -> Result<()> {
         // Delete from file system
@@ -424,47 +470,26 @@ impl ThemeService {
         Ok(())
     }
 
-    /// Get widget assignments for a theme
-    pub async fn get_widget_assignments(&self, theme_id: &str) -> Result<serde_json::Value> {
-        let assignments = self.repo().get_widget_assignments(theme_id, None).await?;
-
-        let result: Vec<serde_json::Value> = assignments
+    /// Menu assigned to each location of a theme
+    pub async fn menu_locations(&self, theme_id: &str) -> Result<HashMap<String, Uuid>> {
+        let assignments = self.repo().get_menu_assignments(theme_id).await?;
+        Ok(assignments
             .into_iter()
-            .map(|a| {
-                serde_json::json!({
-                    "area_slug": a.area_slug,
-                    "widget_id": a.widget_id,
-                    "position": a.position,
-                    "is_active": a.is_active
-                })
-            })
-            .collect();
-
-        Ok(serde_json::json!(result))
+            .map(|a| (a.location_slug, a.menu_id))
+            .collect())
     }
 
-    /// Update widget assignments
-    pub async fn update_widget_assignments(
+    /// Assign a menu to a theme location, or clear the location
+    pub async fn assign_menu(
         &self,
         theme_id: &str,
-        assignments: serde_json::Value,
+        location: &str,
+        menu_id: Option<Uuid>,
     ) -> Result<()> {
-        if let Some(arr) = assignments.as_array() {
-            for item in arr {
-                if let (Some(area), Some(widget_id), Some(position)) = (
-                    item.get("area_slug").and_then(|v| v.as_str()),
-                    item.get("widget_id").and_then(|v| v.as_str()),
-                    item.get("position").and_then(|v| v.as_i64()),
-                ) {
-                    let widget_uuid = Uuid::parse_str(widget_id)
-                        .map_err(|_| Error::validation("Invalid widget_id UUID"))?;
-                    self.repo()
-                        .assign_widget(theme_id, area, widget_uuid, position as i32)
-                        .await?;
-                }
-            }
+        match menu_id {
+            Some(menu_id) => self.repo().assign_menu(theme_id, location, menu_id).await,
+            None => self.repo().unassign_menu(theme_id, location).await,
         }
-        Ok(())
     }
 
     /// Create a preview session
@@ -517,6 +542,14 @@ impl ThemeService {
         let theme_id = validation.theme_id.unwrap();
         let theme_name = validation.theme_name.unwrap_or_else(|| theme_id.clone());
 
+        if !self.file_manager.is_allowed(&theme_id) {
+            return Err(Error::extension_not_allowed(
+                "Theme",
+                theme_id,
+                "this network",
+            ));
+        }
+
         // Check if theme already exists
         let existing = self.repo().find_by_theme_id(&theme_id).await?;
         if existing.is_some() {
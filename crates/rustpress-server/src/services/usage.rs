@@ -0,0 +1,650 @@
+//! Per-site quotas and usage metering
+//!
+//! Hosting providers bill the sites of a network by plan. A site's quotas
+//! are those set for it alone, or else those of its plan (`free`,
+//! `starter`, `professional` or `enterprise`); sites with neither are
+//! unlimited, as is the main site. Four resources are metered:
+//!
+//! - storage bytes and media files, recorded by the storage as files are
+//!   kept and deleted; uploads that would go over quota are refused
+//! - bandwidth, the bytes of every response a site serves; sites out of
+//!   bandwidth answer 403 until the next month, except to the network API
+//! - job time, the seconds background jobs dispatched by a site run; jobs
+//!   of sites out of job time wait for the next month
+//!
+//! Usage is counted in memory and added to the month's row of
+//! `site_usage` every [`USAGE_FLUSH_INTERVAL`]. Storage and media carry
+//! over from month to month; bandwidth and job time start over.
+
+use async_trait::async_trait;
+use chrono::{Datelike, NaiveDate, Utc};
+use parking_lot::Mutex;
+use rustpress_core::error::{Error, Result};
+use rustpress_core::tenant::{MeteredResource, TenantPlan, TenantQuotas, TenantUsage, UsageMeter};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use sqlx::{FromRow, PgPool};
+use std::collections::{BTreeMap, HashMap};
+use std::mem;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+/// How often buffered usage is written to `site_usage`
+pub const USAGE_FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How long a site's plan and stored usage are reused for quota checks
+const QUOTA_CACHE_TTL: Duration = Duration::from_secs(30);
+
+/// Earlier months in a site's usage report
+const REPORT_MONTHS: i64 = 12;
+
+/// Longest plan name
+const MAX_PLAN_LENGTH: usize = 50;
+
+/// First day of the month `date` falls in
+pub fn month_of(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).unwrap_or(date)
+}
+
+fn current_month() -> NaiveDate {
+    month_of(Utc::now().date_naive())
+}
+
+/// Month from `YYYY-MM`
+pub fn parse_period(period: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(&format!("{}-01", period.trim()), "%Y-%m-%d")
+        .map_err(|_| Error::invalid_input("period", "Periods are months as YYYY-MM"))
+}
+
+/// Usage of a site in one month
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, FromRow)]
+pub struct SiteUsage {
+    /// First day of the month
+    pub period: NaiveDate,
+    pub storage_bytes: i64,
+    pub media_count: i64,
+    pub bandwidth_bytes: i64,
+    pub job_seconds: i64,
+}
+
+impl SiteUsage {
+    fn empty(period: NaiveDate) -> Self {
+        Self {
+            period,
+            storage_bytes: 0,
+            media_count: 0,
+            bandwidth_bytes: 0,
+            job_seconds: 0,
+        }
+    }
+
+    /// Usage in `period` when `self` is the latest month before it with
+    /// any; storage and media carry over
+    fn carried_to(self, period: NaiveDate) -> Self {
+        if self.period == period {
+            return self;
+        }
+        Self {
+            period,
+            bandwidth_bytes: 0,
+            job_seconds: 0,
+            ..self
+        }
+    }
+
+    fn add(&mut self, resource: MeteredResource, amount: i64) {
+        let counter = match resource {
+            MeteredResource::Storage => &mut self.storage_bytes,
+            MeteredResource::Media => &mut self.media_count,
+            MeteredResource::Bandwidth => &mut self.bandwidth_bytes,
+            MeteredResource::JobTime => &mut self.job_seconds,
+        };
+        *counter = counter.saturating_add(amount);
+    }
+
+    /// Add the usage counted in `other`; only storage and media when it
+    /// is of another month
+    fn merge(&mut self, other: &SiteUsage) {
+        self.add(MeteredResource::Storage, other.storage_bytes);
+        self.add(MeteredResource::Media, other.media_count);
+        if other.period == self.period {
+            self.add(MeteredResource::Bandwidth, other.bandwidth_bytes);
+            self.add(MeteredResource::JobTime, other.job_seconds);
+        }
+    }
+
+    fn tenant_usage(&self) -> TenantUsage {
+        let amount = |n: i64| n.max(0) as u64;
+        TenantUsage {
+            storage_bytes: amount(self.storage_bytes),
+            media_count: self.media_count.clamp(0, u32::MAX as i64) as u32,
+            bandwidth_bytes_this_month: amount(self.bandwidth_bytes),
+            job_seconds_this_month: amount(self.job_seconds),
+            ..Default::default()
+        }
+    }
+}
+
+/// Plan and quotas in force for a site
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SitePlan {
+    pub plan: Option<String>,
+    /// Whether the quotas were set for the site alone
+    pub custom_quotas: bool,
+    pub quotas: TenantQuotas,
+}
+
+impl SitePlan {
+    fn resolve(plan: Option<String>, quotas: Option<TenantQuotas>) -> Self {
+        let custom_quotas = quotas.is_some();
+        let quotas = quotas.unwrap_or_else(|| match plan.as_deref() {
+            Some(plan) => TenantQuotas::for_plan(&TenantPlan::parse(plan)),
+            None => TenantQuotas::unlimited(),
+        });
+        Self {
+            plan,
+            custom_quotas,
+            quotas,
+        }
+    }
+}
+
+/// Plan and quotas to set for a site
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SitePlanInput {
+    /// Plan the site is billed on; none for unlimited
+    pub plan: Option<String>,
+    /// Quotas replacing those of the plan; limits left out are unlimited
+    pub quotas: Option<TenantQuotas>,
+}
+
+impl SitePlanInput {
+    fn normalize(self) -> Result<Self> {
+        let plan = self
+            .plan
+            .map(|p| p.trim().to_lowercase())
+            .filter(|p| !p.is_empty());
+        if let Some(plan) = &plan {
+            let valid = plan.len() <= MAX_PLAN_LENGTH
+                && plan
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+            if !valid {
+                return Err(Error::invalid_input(
+                    "plan",
+                    "Plans are up to 50 letters, digits, hyphens and underscores",
+                ));
+            }
+        }
+        Ok(Self {
+            plan,
+            quotas: self.quotas,
+        })
+    }
+}
+
+/// Quotas and usage of a site
+#[derive(Debug, Clone, Serialize)]
+pub struct SiteUsageReport {
+    pub site_id: Uuid,
+    #[serde(flatten)]
+    pub plan: SitePlan,
+    /// Usage this month, including what is not written yet
+    pub usage: SiteUsage,
+    /// Quotas the site has used up
+    pub violations: Vec<String>,
+    /// Earlier months, newest first
+    pub history: Vec<SiteUsage>,
+}
+
+/// Usage of one site in a month, for billing
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct SiteBillingUsage {
+    pub site_id: Uuid,
+    pub slug: String,
+    pub plan: Option<String>,
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    pub usage: SiteUsage,
+}
+
+/// Plan of a site and its latest month of usage, as stored
+#[derive(Debug, FromRow)]
+struct StoredSite {
+    plan: Option<String>,
+    quotas: Option<Json<TenantQuotas>>,
+    period: Option<NaiveDate>,
+    storage_bytes: Option<i64>,
+    media_count: Option<i64>,
+    bandwidth_bytes: Option<i64>,
+    job_seconds: Option<i64>,
+}
+
+impl StoredSite {
+    fn into_parts(self, period: NaiveDate) -> (SitePlan, SiteUsage) {
+        let usage = match self.period {
+            Some(stored) => SiteUsage {
+                period: stored,
+                storage_bytes: self.storage_bytes.unwrap_or(0),
+                media_count: self.media_count.unwrap_or(0),
+                bandwidth_bytes: self.bandwidth_bytes.unwrap_or(0),
+                job_seconds: self.job_seconds.unwrap_or(0),
+            }
+            .carried_to(period),
+            None => SiteUsage::empty(period),
+        };
+        let plan = SitePlan::resolve(self.plan, self.quotas.map(|Json(q)| q));
+        (plan, usage)
+    }
+}
+
+/// Plan and stored usage of a site, loaded at an instant
+type CachedSite = (Instant, Arc<(SitePlan, SiteUsage)>);
+
+/// Quotas and metered usage of network sites
+pub struct UsageService {
+    pool: PgPool,
+    /// Usage not written yet, by site and month
+    pending: Mutex<BTreeMap<(Uuid, NaiveDate), SiteUsage>>,
+    sites: Mutex<HashMap<Uuid, CachedSite>>,
+}
+
+impl UsageService {
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            pending: Mutex::new(BTreeMap::new()),
+            sites: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Count usage of a site; it is written on the next flush
+    pub fn add(&self, site: Uuid, resource: MeteredResource, amount: i64) {
+        if amount == 0 {
+            return;
+        }
+        let period = current_month();
+        self.pending
+            .lock()
+            .entry((site, period))
+            .or_insert_with(|| SiteUsage::empty(period))
+            .add(resource, amount);
+    }
+
+    /// Stored usage of a site plus what is not written yet
+    fn with_pending(&self, site: Uuid, mut usage: SiteUsage) -> SiteUsage {
+        let pending = self.pending.lock();
+        let months = pending.range((site, NaiveDate::MIN)..=(site, NaiveDate::MAX));
+        for (_, counted) in months {
+            usage.merge(counted);
+        }
+        usage
+    }
+
+    /// Plan and stored usage of a site in `period`; None for unknown sites
+    async fn load(&self, site: Uuid, period: NaiveDate) -> Result<Option<(SitePlan, SiteUsage)>> {
+        let stored: Option<StoredSite> = sqlx::query_as(
+            r#"
+            SELECT s.plan, s.quotas, u.period, u.storage_bytes, u.media_count,
+                   u.bandwidth_bytes, u.job_seconds
+            FROM sites s
+            LEFT JOIN LATERAL (
+                SELECT period, storage_bytes, media_count, bandwidth_bytes, job_seconds
+                FROM site_usage
+                WHERE site_id = s.id AND period <= $2
+                ORDER BY period DESC
+                LIMIT 1
+            ) u ON TRUE
+            WHERE s.id = $1
+            "#,
+        )
+        .bind(site)
+        .bind(period)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load site usage", e))?;
+
+        Ok(stored.map(|stored| stored.into_parts(period)))
+    }
+
+    /// Plan and stored usage for quota checks, reused for a while
+    async fn snapshot(&self, site: Uuid) -> Result<Arc<(SitePlan, SiteUsage)>> {
+        if let Some((loaded_at, snapshot)) = self.sites.lock().get(&site) {
+            if loaded_at.elapsed() < QUOTA_CACHE_TTL {
+                return Ok(snapshot.clone());
+            }
+        }
+
+        let period = current_month();
+        let snapshot = Arc::new(
+            self.load(site, period)
+                .await?
+                .unwrap_or_else(|| (SitePlan::resolve(None, None), SiteUsage::empty(period))),
+        );
+        self.sites
+            .lock()
+            .insert(site, (Instant::now(), snapshot.clone()));
+        Ok(snapshot)
+    }
+
+    /// Quotas and usage of a site, with the months before
+    pub async fn report(&self, site: Uuid) -> Result<SiteUsageReport> {
+        let period = current_month();
+        let (plan, usage) = self
+            .load(site, period)
+            .await?
+            .ok_or_else(|| Error::not_found("Site", site.to_string()))?;
+        let usage = self.with_pending(site, usage);
+        let violations = usage
+            .tenant_usage()
+            .quota_violations(&plan.quotas)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+
+        let history = sqlx::query_as(
+            r#"
+            SELECT period, storage_bytes, media_count, bandwidth_bytes, job_seconds
+            FROM site_usage
+            WHERE site_id = $1 AND period < $2
+            ORDER BY period DESC
+            LIMIT $3
+            "#,
+        )
+        .bind(site)
+        .bind(period)
+        .bind(REPORT_MONTHS)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load site usage history", e))?;
+
+        Ok(SiteUsageReport {
+            site_id: site,
+            plan,
+            usage,
+            violations,
+            history,
+        })
+    }
+
+    /// Usage of every site in a month, for billing
+    pub async fn billing_report(&self, period: NaiveDate) -> Result<Vec<SiteBillingUsage>> {
+        if let Err(e) = self.flush_usage().await {
+            tracing::warn!("Failed to flush site usage: {}", e);
+        }
+
+        sqlx::query_as(
+            r#"
+            SELECT s.id AS site_id, s.slug, s.plan, $1::date AS period,
+                   COALESCE(u.storage_bytes, 0) AS storage_bytes,
+                   COALESCE(u.media_count, 0) AS media_count,
+                   CASE WHEN u.period = $1 THEN u.bandwidth_bytes ELSE 0 END AS bandwidth_bytes,
+                   CASE WHEN u.period = $1 THEN u.job_seconds ELSE 0 END AS job_seconds
+            FROM sites s
+            LEFT JOIN LATERAL (
+                SELECT period, storage_bytes, media_count, bandwidth_bytes, job_seconds
+                FROM site_usage
+                WHERE site_id = s.id AND period <= $1
+                ORDER BY period DESC
+                LIMIT 1
+            ) u ON TRUE
+            ORDER BY s.slug
+            "#,
+        )
+        .bind(month_of(period))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load network usage", e))
+    }
+
+    /// Put a site on a plan, or give it quotas of its own
+    pub async fn set_plan(&self, site: Uuid, input: SitePlanInput) -> Result<SitePlan> {
+        let input = input.normalize()?;
+        let stored: Option<(Option<String>, Option<Json<TenantQuotas>>)> = sqlx::query_as(
+            r#"
+            UPDATE sites SET plan = $2, quotas = $3, updated_at = NOW()
+            WHERE id = $1
+            RETURNING plan, quotas
+            "#,
+        )
+        .bind(site)
+        .bind(&input.plan)
+        .bind(input.quotas.map(Json))
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to save site plan", e))?;
+
+        let (plan, quotas) = stored.ok_or_else(|| Error::not_found("Site", site.to_string()))?;
+        self.sites.lock().remove(&site);
+        Ok(SitePlan::resolve(plan, quotas.map(|Json(q)| q)))
+    }
+
+    /// Write buffered usage to `site_usage`. Usage is put back when the
+    /// write fails, so the next flush retries it.
+    pub async fn flush_usage(&self) -> Result<()> {
+        let pending = mem::take(&mut *self.pending.lock());
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        let written = self.write_usage(&pending).await;
+        if written.is_err() {
+            let mut buffer = self.pending.lock();
+            for (key, counted) in pending {
+                buffer
+                    .entry(key)
+                    .and_modify(|usage| usage.merge(&counted))
+                    .or_insert(counted);
+            }
+        } else {
+            let mut sites = self.sites.lock();
+            for (site, _) in pending.keys() {
+                sites.remove(site);
+            }
+        }
+        written
+    }
+
+    async fn write_usage(&self, pending: &BTreeMap<(Uuid, NaiveDate), SiteUsage>) -> Result<()> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| Error::database_with_source("Failed to start usage flush", e))?;
+
+        // Months of a site in order, so each starts from the one before;
+        // usage of deleted sites is dropped
+        for ((site, period), usage) in pending {
+            sqlx::query(
+                r#"
+                INSERT INTO site_usage
+                    (site_id, period, storage_bytes, media_count, bandwidth_bytes, job_seconds)
+                SELECT $1, $2,
+                       GREATEST(COALESCE(prev.storage_bytes, 0) + $3, 0),
+                       GREATEST(COALESCE(prev.media_count, 0) + $4, 0),
+                       $5, $6
+                FROM (SELECT 1) AS one
+                LEFT JOIN LATERAL (
+                    SELECT storage_bytes, media_count
+                    FROM site_usage
+                    WHERE site_id = $1 AND period < $2
+                    ORDER BY period DESC
+                    LIMIT 1
+                ) prev ON TRUE
+                WHERE EXISTS (SELECT 1 FROM sites WHERE id = $1)
+                ON CONFLICT (site_id, period) DO UPDATE SET
+                    storage_bytes = GREATEST(site_usage.storage_bytes + $3, 0),
+                    media_count = GREATEST(site_usage.media_count + $4, 0),
+                    bandwidth_bytes = site_usage.bandwidth_bytes + $5,
+                    job_seconds = site_usage.job_seconds + $6,
+                    updated_at = NOW()
+                "#,
+            )
+            .bind(site)
+            .bind(period)
+            .bind(usage.storage_bytes)
+            .bind(usage.media_count)
+            .bind(usage.bandwidth_bytes)
+            .bind(usage.job_seconds)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to write site usage", e))?;
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| Error::database_with_source("Failed to commit usage flush", e))
+    }
+
+    /// Flush buffered usage every `every`
+    pub fn spawn_usage_flush(self: &Arc<Self>, every: Duration) -> JoinHandle<()> {
+        let service = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(every);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = service.flush_usage().await {
+                    tracing::warn!("Failed to flush site usage: {}", e);
+                }
+            }
+        })
+    }
+}
+
+#[async_trait]
+impl UsageMeter for UsageService {
+    /// Sites whose quotas cannot be loaded are let through
+    async fn check(&self, site: Uuid, resource: MeteredResource, amount: u64) -> Result<()> {
+        let snapshot = match self.snapshot(site).await {
+            Ok(snapshot) => snapshot,
+            Err(e) => {
+                tracing::warn!(site = %site, error = %e, "Failed to load site quotas");
+                return Ok(());
+            }
+        };
+        let (plan, usage) = &*snapshot;
+        if plan.quotas.limit(resource).is_none() {
+            return Ok(());
+        }
+
+        self.with_pending(site, *usage)
+            .tenant_usage()
+            .check(&plan.quotas, resource, amount)
+    }
+
+    async fn record(&self, site: Uuid, resource: MeteredResource, amount: i64) {
+        self.add(site, resource, amount);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn month(year: i32, month: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, 1).unwrap()
+    }
+
+    #[test]
+    fn test_usage_carries_storage_over() {
+        let october = SiteUsage {
+            period: month(2026, 10),
+            storage_bytes: 100,
+            media_count: 2,
+            bandwidth_bytes: 5000,
+            job_seconds: 60,
+        };
+        let mut november = october.carried_to(month(2026, 11));
+        assert_eq!(november.storage_bytes, 100);
+        assert_eq!(november.bandwidth_bytes, 0);
+
+        // Unwritten October usage still counts towards storage
+        november.merge(&october);
+        assert_eq!(november.storage_bytes, 200);
+        assert_eq!(november.media_count, 4);
+        assert_eq!(november.job_seconds, 0);
+    }
+
+    #[test]
+    fn test_site_plan_resolution() {
+        let plan = SitePlan::resolve(Some("starter".to_string()), None);
+        assert_eq!(plan.quotas, TenantQuotas::starter_tier());
+        assert!(!plan.custom_quotas);
+
+        let custom = TenantQuotas {
+            max_media: Some(10),
+            ..TenantQuotas::unlimited()
+        };
+        let plan = SitePlan::resolve(Some("starter".to_string()), Some(custom.clone()));
+        assert_eq!(plan.quotas, custom);
+        assert!(plan.custom_quotas);
+
+        assert_eq!(
+            SitePlan::resolve(None, None).quotas,
+            TenantQuotas::unlimited()
+        );
+    }
+
+    #[test]
+    fn test_plan_input_and_period_validation() {
+        let input = SitePlanInput {
+            plan: Some(" Pro-2026 ".to_string()),
+            quotas: None,
+        };
+        assert_eq!(input.normalize().unwrap().plan.as_deref(), Some("pro-2026"));
+        assert!(SitePlanInput {
+            plan: Some("gold plan".to_string()),
+            quotas: None,
+        }
+        .normalize()
+        .is_err());
+
+        assert_eq!(parse_period("2026-02").unwrap(), month(2026, 2));
+        assert!(parse_period("2026-13").is_err());
+        assert_eq!(
+            month_of(NaiveDate::from_ymd_opt(2026, 2, 17).unwrap()),
+            month(2026, 2)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_check_counts_unwritten_usage() {
+        let service =
+            UsageService::new(PgPool::connect_lazy("postgres://localhost/rustpress_test").unwrap());
+        let site = Uuid::new_v4();
+        let plan = SitePlan::resolve(
+            None,
+            Some(TenantQuotas {
+                max_bandwidth_bytes_per_month: Some(1000),
+                ..TenantQuotas::unlimited()
+            }),
+        );
+        let usage = SiteUsage {
+            bandwidth_bytes: 600,
+            ..SiteUsage::empty(current_month())
+        };
+        service
+            .sites
+            .lock()
+            .insert(site, (Instant::now(), Arc::new((plan, usage))));
+
+        assert!(service
+            .check(site, MeteredResource::Bandwidth, 1)
+            .await
+            .is_ok());
+        service.add(site, MeteredResource::Bandwidth, 400);
+        let err = service
+            .check(site, MeteredResource::Bandwidth, 1)
+            .await
+            .unwrap_err();
+        assert_eq!(err.error_code(), "QUOTA_EXCEEDED");
+
+        // Resources without a limit are never refused
+        assert!(service
+            .check(site, MeteredResource::Storage, u64::MAX)
+            .await
+            .is_ok());
+    }
+}
@@ -0,0 +1,543 @@
+//! User API Keys
+//!
+//! Long-lived keys that act as the user who created them, for scripts and
+//! for running the CLI against a live site (`rustpress --remote URL
+//! --api-key KEY`). They are sent as bearer tokens in place of a session
+//! token and are accepted wherever [`AuthUser`](crate::extract::AuthUser)
+//! is, with two limits on top of the owner's role:
+//!
+//! - every key carries scopes such as `posts:read` or `*:write`; a request
+//!   needs the scope of the resource it touches (the first path segment
+//!   under `/api/v1`) and `read` for `GET`/`HEAD`, `write` for anything else
+//! - keys cannot create, list or revoke keys
+//!
+//! Only the SHA-256 hash of a key is stored; the secret is shown once, when
+//! the key is created. Keys stop working when revoked, when they expire or
+//! when their owner is no longer active.
+
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use axum::http::Method;
+use base64::Engine as _;
+use chrono::{DateTime, Duration, Utc};
+use rustpress_auth::ApiKeyScope;
+use rustpress_core::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::types::Json;
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+
+/// Prefix of every user API key; it also tells keys apart from session
+/// tokens in the `Authorization` header
+pub const USER_API_KEY_PREFIX: &str = "rpu_";
+
+/// Claim set on requests authenticated with a key, holding the key's id
+pub const API_KEY_CLAIM: &str = "api_key_id";
+
+/// Resources the admin API is split into, as reported to clients
+pub const API_RESOURCES: [&str; 19] = [
+    "auth",
+    "backups",
+    "cache",
+    "compat",
+    "cron",
+    "db",
+    "export",
+    "groups",
+    "import",
+    "maintenance",
+    "media",
+    "pages",
+    "plugins",
+    "posts",
+    "redirects",
+    "seo",
+    "settings",
+    "themes",
+    "users",
+];
+
+/// Paths any valid key may read, whatever its scopes, so clients can find
+/// out who they are and what they may do
+const ALWAYS_READABLE: [&str; 3] = [
+    "/api/v1/auth/capabilities",
+    "/api/v1/auth/me",
+    "/api/v1/users/me",
+];
+
+/// Longest accepted key name
+const MAX_KEY_NAME_LENGTH: usize = 100;
+
+/// Most keys a user can hold at once
+const MAX_KEYS_PER_USER: i64 = 20;
+
+/// Most scopes a key can carry
+const MAX_SCOPES: usize = 32;
+
+/// Longest lifetime a key can be given
+const MAX_EXPIRY_DAYS: u32 = 3650;
+
+/// Random bytes in a key, 40 characters once encoded
+const KEY_SECRET_BYTES: usize = 30;
+
+/// Characters of the key kept in clear for display
+const KEY_DISPLAY_LENGTH: usize = 12;
+
+const KEY_COLUMNS: &str =
+    "id, user_id, name, key_prefix, scopes, expires_at, last_used_at, revoked_at, created_at";
+
+/// A user API key, without its secret
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct UserApiKey {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    /// Start of the key, for telling keys apart
+    pub key_prefix: String,
+    /// `resource:action` pairs, `*` matching any resource or action
+    pub scopes: Json<Vec<String>>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl UserApiKey {
+    /// Whether the key grants `scope`
+    pub fn allows(&self, scope: &ApiKeyScope) -> bool {
+        scopes_allow(&self.scopes, scope)
+    }
+}
+
+/// A newly created key; `secret` is never shown again
+#[derive(Debug, Clone, Serialize)]
+pub struct CreatedUserApiKey {
+    #[serde(flatten)]
+    pub key: UserApiKey,
+    pub secret: String,
+}
+
+/// Name, scopes and lifetime of a key as submitted
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct UserApiKeyInput {
+    pub name: String,
+    /// Full access (`*:*`) when empty
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    /// Never expires when absent
+    #[serde(default)]
+    pub expires_in_days: Option<u32>,
+}
+
+impl UserApiKeyInput {
+    fn normalize(mut self) -> Result<Self> {
+        self.name = self.name.trim().to_string();
+        if self.name.is_empty() {
+            return Err(Error::invalid_input("name", "Name is required"));
+        }
+        if self.name.chars().count() > MAX_KEY_NAME_LENGTH {
+            return Err(Error::invalid_input(
+                "name",
+                format!("Must be at most {} characters", MAX_KEY_NAME_LENGTH),
+            ));
+        }
+
+        if self.scopes.len() > MAX_SCOPES {
+            return Err(Error::invalid_input(
+                "scopes",
+                format!("At most {} scopes can be granted", MAX_SCOPES),
+            ));
+        }
+        let mut scopes: Vec<String> = Vec::with_capacity(self.scopes.len().max(1));
+        for scope in &self.scopes {
+            let scope = scope.trim().to_ascii_lowercase();
+            if parse_scope(&scope).is_none() {
+                return Err(Error::invalid_input(
+                    "scopes",
+                    format!(
+                        "'{}' is not a scope like posts:read, users:write or *:*",
+                        scope
+                    ),
+                ));
+            }
+            if !scopes.contains(&scope) {
+                scopes.push(scope);
+            }
+        }
+        if scopes.is_empty() {
+            scopes.push(ApiKeyScope::full_access().to_string());
+        }
+        self.scopes = scopes;
+
+        if let Some(days) = self.expires_in_days {
+            if days == 0 || days > MAX_EXPIRY_DAYS {
+                return Err(Error::invalid_input(
+                    "expires_in_days",
+                    format!("Must be between 1 and {}", MAX_EXPIRY_DAYS),
+                ));
+            }
+        }
+
+        Ok(self)
+    }
+}
+
+/// The user a key acts as, loaded with the key on every request
+#[derive(Debug, Clone, FromRow)]
+pub struct ApiKeyOwner {
+    pub key_id: Uuid,
+    pub user_id: Uuid,
+    pub email: String,
+    pub role: String,
+    pub scopes: Json<Vec<String>>,
+}
+
+impl ApiKeyOwner {
+    /// Whether the key grants `scope`
+    pub fn allows(&self, scope: &ApiKeyScope) -> bool {
+        scopes_allow(&self.scopes, scope)
+    }
+}
+
+/// Access to one resource, as reported by the capabilities endpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ResourceAccess {
+    pub read: bool,
+    pub write: bool,
+}
+
+/// What the caller may do through the API
+#[derive(Debug, Clone, Serialize)]
+pub struct ApiCapabilities {
+    pub server_version: String,
+    pub api_version: String,
+    pub user_id: Uuid,
+    pub email: Option<String>,
+    pub roles: Vec<String>,
+    /// `api_key` or `session`
+    pub auth: String,
+    /// Scopes of the key; `*:*` for sessions
+    pub scopes: Vec<String>,
+    pub resources: std::collections::BTreeMap<String, ResourceAccess>,
+}
+
+impl ApiCapabilities {
+    /// Capabilities of a caller with `roles`, limited to `key_scopes` when
+    /// it authenticated with a key
+    pub fn new(
+        user_id: Uuid,
+        email: Option<String>,
+        roles: Vec<String>,
+        key_scopes: Option<&[String]>,
+    ) -> Self {
+        let scopes: Vec<String> = match key_scopes {
+            Some(scopes) => scopes.to_vec(),
+            None => vec![ApiKeyScope::full_access().to_string()],
+        };
+        let allows = |resource: &str, action: &str| {
+            scopes_allow(&scopes, &ApiKeyScope::new(resource, action))
+        };
+
+        let resources = API_RESOURCES
+            .iter()
+            .map(|resource| {
+                (
+                    resource.to_string(),
+                    ResourceAccess {
+                        read: allows(resource, "read"),
+                        write: allows(resource, "write"),
+                    },
+                )
+            })
+            .collect();
+
+        Self {
+            server_version: env!("CARGO_PKG_VERSION").to_string(),
+            api_version: "v1".to_string(),
+            user_id,
+            email,
+            roles,
+            auth: if key_scopes.is_some() {
+                "api_key"
+            } else {
+                "session"
+            }
+            .to_string(),
+            scopes,
+            resources,
+        }
+    }
+}
+
+/// Parse a `resource:action` scope; actions are `read`, `write` or `*`
+pub fn parse_scope(scope: &str) -> Option<ApiKeyScope> {
+    let (resource, action) = scope.split_once(':')?;
+    let resource_ok = resource == "*"
+        || (!resource.is_empty()
+            && resource
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_'));
+    let action_ok = matches!(action, "read" | "write" | "*");
+    (resource_ok && action_ok).then(|| ApiKeyScope::new(resource, action))
+}
+
+/// Whether any of `scopes` covers `scope`
+fn scopes_allow(scopes: &[String], scope: &ApiKeyScope) -> bool {
+    scopes
+        .iter()
+        .filter_map(|s| parse_scope(s))
+        .any(|granted| granted.covers(scope))
+}
+
+/// Scope a request needs; `None` for paths any valid key may use
+pub fn required_scope(method: &Method, path: &str) -> Option<ApiKeyScope> {
+    let path = path.trim_end_matches('/');
+    let read = matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS);
+    if read && ALWAYS_READABLE.contains(&path) {
+        return None;
+    }
+
+    let resource = path
+        .strip_prefix("/api/v1/")
+        .or_else(|| path.strip_prefix('/'))
+        .unwrap_or(path)
+        .split('/')
+        .next()
+        .filter(|segment| !segment.is_empty())
+        .unwrap_or("*");
+    Some(ApiKeyScope::new(
+        resource,
+        if read { "read" } else { "write" },
+    ))
+}
+
+/// Whether a bearer token is a user API key rather than a session token
+pub fn is_user_api_key(token: &str) -> bool {
+    token.starts_with(USER_API_KEY_PREFIX)
+}
+
+/// User API keys
+pub struct UserApiKeyService {
+    pool: PgPool,
+}
+
+impl UserApiKeyService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Keys of a user, newest first, revoked ones included
+    pub async fn list_keys(&self, user_id: Uuid) -> Result<Vec<UserApiKey>> {
+        sqlx::query_as(&format!(
+            "SELECT {} FROM user_api_keys WHERE user_id = $1 ORDER BY created_at DESC",
+            KEY_COLUMNS
+        ))
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to list API keys", e))
+    }
+
+    /// Create a key for `user_id`; the returned secret is the only copy
+    pub async fn create_key(
+        &self,
+        user_id: Uuid,
+        input: UserApiKeyInput,
+    ) -> Result<CreatedUserApiKey> {
+        let input = input.normalize()?;
+
+        let (active,): (i64,) = sqlx::query_as(
+            r#"
+            SELECT COUNT(*) FROM user_api_keys
+            WHERE user_id = $1 AND revoked_at IS NULL
+              AND (expires_at IS NULL OR expires_at > NOW())
+            "#,
+        )
+        .bind(user_id)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to count API keys", e))?;
+        if active >= MAX_KEYS_PER_USER {
+            return Err(Error::validation(format!(
+                "At most {} active API keys per user; revoke one first",
+                MAX_KEYS_PER_USER
+            )));
+        }
+
+        let secret = generate_secret();
+        let expires_at = input
+            .expires_in_days
+            .map(|days| Utc::now() + Duration::days(days as i64));
+
+        let key: UserApiKey = sqlx::query_as(&format!(
+            r#"
+            INSERT INTO user_api_keys (user_id, name, key_prefix, key_hash, scopes, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING {}
+            "#,
+            KEY_COLUMNS
+        ))
+        .bind(user_id)
+        .bind(&input.name)
+        .bind(&secret[..KEY_DISPLAY_LENGTH])
+        .bind(hash_secret(&secret))
+        .bind(Json(&input.scopes))
+        .bind(expires_at)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to create API key", e))?;
+
+        Ok(CreatedUserApiKey { key, secret })
+    }
+
+    /// Revoke a key; only its owner may, unless `any_owner` is set
+    pub async fn revoke_key(&self, id: Uuid, user_id: Uuid, any_owner: bool) -> Result<UserApiKey> {
+        sqlx::query_as(&format!(
+            r#"
+            UPDATE user_api_keys
+            SET revoked_at = COALESCE(revoked_at, NOW())
+            WHERE id = $1 AND ($2 OR user_id = $3)
+            RETURNING {}
+            "#,
+            KEY_COLUMNS
+        ))
+        .bind(id)
+        .bind(any_owner)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to revoke API key", e))?
+        .ok_or_else(|| Error::not_found("API key", id.to_string()))
+    }
+
+    /// The active user behind a key secret, or `None` when the key is
+    /// unknown, revoked or expired. Records the use.
+    pub async fn authenticate(&self, secret: &str) -> Result<Option<ApiKeyOwner>> {
+        if !is_user_api_key(secret) {
+            return Ok(None);
+        }
+
+        sqlx::query_as(
+            r#"
+            UPDATE user_api_keys k
+            SET last_used_at = NOW()
+            FROM users u
+            WHERE k.key_hash = $1
+              AND k.revoked_at IS NULL
+              AND (k.expires_at IS NULL OR k.expires_at > NOW())
+              AND u.id = k.user_id
+              AND u.status = 'active'
+            RETURNING k.id AS key_id, u.id AS user_id, u.email, u.role, k.scopes
+            "#,
+        )
+        .bind(hash_secret(secret))
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to check API key", e))
+    }
+}
+
+fn generate_secret() -> String {
+    let mut bytes = [0u8; KEY_SECRET_BYTES];
+    OsRng.fill_bytes(&mut bytes);
+    format!(
+        "{}{}",
+        USER_API_KEY_PREFIX,
+        base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes)
+    )
+}
+
+fn hash_secret(secret: &str) -> String {
+    format!("{:x}", Sha256::digest(secret.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_required_scope() {
+        let scope = required_scope(&Method::GET, "/api/v1/posts/42").unwrap();
+        assert_eq!(scope.to_string(), "posts:read");
+
+        let scope = required_scope(&Method::DELETE, "/api/v1/users/42").unwrap();
+        assert_eq!(scope.to_string(), "users:write");
+
+        let scope = required_scope(&Method::POST, "/api/v1/cache/clear").unwrap();
+        assert_eq!(scope.to_string(), "cache:write");
+
+        assert!(required_scope(&Method::GET, "/api/v1/auth/capabilities").is_none());
+        assert!(required_scope(&Method::GET, "/api/v1/users/me/").is_none());
+        // Writing to an always-readable path still needs its scope
+        assert!(required_scope(&Method::PUT, "/api/v1/users/me").is_some());
+    }
+
+    #[test]
+    fn test_scope_parsing_and_normalize() {
+        assert!(parse_scope("posts:read").is_some());
+        assert!(parse_scope("*:*").is_some());
+        assert!(parse_scope("posts").is_none());
+        assert!(parse_scope("posts:delete").is_none());
+        assert!(parse_scope("Posts:read").is_none());
+
+        let input = UserApiKeyInput {
+            name: "  deploy  ".to_string(),
+            scopes: vec!["Posts:Read".to_string(), "posts:read".to_string()],
+            expires_in_days: Some(30),
+        }
+        .normalize()
+        .unwrap();
+        assert_eq!(input.name, "deploy");
+        assert_eq!(input.scopes, vec!["posts:read".to_string()]);
+
+        let input = UserApiKeyInput {
+            name: "ci".to_string(),
+            ..Default::default()
+        }
+        .normalize()
+        .unwrap();
+        assert_eq!(input.scopes, vec!["*:*".to_string()]);
+
+        let err = UserApiKeyInput {
+            name: "ci".to_string(),
+            scopes: vec!["posts:delete".to_string()],
+            expires_in_days: None,
+        }
+        .normalize();
+        assert!(err.is_err());
+    }
+
+    #[test]
+    fn test_capabilities() {
+        let scopes = vec!["posts:*".to_string(), "*:read".to_string()];
+        let caps =
+            ApiCapabilities::new(Uuid::nil(), None, vec!["editor".to_string()], Some(&scopes));
+        assert_eq!(caps.auth, "api_key");
+        assert_eq!(
+            caps.resources["posts"],
+            ResourceAccess {
+                read: true,
+                write: true
+            }
+        );
+        assert_eq!(
+            caps.resources["users"],
+            ResourceAccess {
+                read: true,
+                write: false
+            }
+        );
+
+        let caps = ApiCapabilities::new(Uuid::nil(), None, vec![], None);
+        assert_eq!(caps.auth, "session");
+        assert!(caps.resources.values().all(|access| access.write));
+    }
+
+    #[test]
+    fn test_secret_format() {
+        let secret = generate_secret();
+        assert!(is_user_api_key(&secret));
+        assert_eq!(secret.len(), USER_API_KEY_PREFIX.len() + 40);
+        assert_eq!(hash_secret(&secret).len(), 64);
+    }
+}
@@ -0,0 +1,933 @@
+//! Bulk User Import
+//!
+//! Imports users from CSV or JSON files, as produced by the user export
+//! (`/api/v1/users/export`) or by other systems. An import is started as a
+//! run whose rows are processed in the background; the run's counters are
+//! updated as rows go through, so the admin UI and `rustpress users import`
+//! can report progress.
+//!
+//! - roles named in the file are translated through a role map and must end
+//!   up as a site role; rows without a role get the default role
+//! - duplicates are detected by email, both against existing users (skipped
+//!   or updated, per the duplicate policy) and within the file (later rows
+//!   are skipped)
+//! - files never carry credentials: imported users get a random password
+//!   and, when invitations are requested, an email with a set-password link
+//!   issued through the [`TokenManager`]
+
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use async_trait::async_trait;
+use base64::Engine as _;
+use chrono::{DateTime, Duration, Utc};
+use rustpress_api::services::user_service::{
+    CreateUserRequest, UpdateUserRequest, UserResponse, UserRole, UserService,
+};
+use rustpress_auth::tokens::TokenConfig;
+use rustpress_auth::{SecureToken, SecureTokenType, TokenManager, TokenStore};
+use rustpress_core::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use sqlx::{FromRow, PgPool};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use super::EmailService;
+
+/// Most rows a single import may contain
+pub const MAX_IMPORT_ROWS: usize = 10_000;
+
+/// Row failures kept on a run; later ones are only counted
+const MAX_REPORTED_ERRORS: usize = 100;
+
+/// Rows processed between progress updates
+const PROGRESS_INTERVAL: usize = 25;
+
+/// How long invitation links stay valid
+pub const INVITATION_DAYS: i64 = 7;
+
+/// Format of an import file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportFormat {
+    #[default]
+    Csv,
+    /// A JSON array of users, or one JSON object per line (the JSONL export)
+    Json,
+}
+
+/// What to do with rows whose email already belongs to a user
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DuplicatePolicy {
+    /// Leave the existing user alone
+    #[default]
+    Skip,
+    /// Update the existing user's name, role, locale and timezone
+    Update,
+}
+
+/// One user read from an import file. Other columns, such as the `id`,
+/// `status` and timestamps of an export, are ignored.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct ImportRecord {
+    #[serde(default)]
+    pub email: String,
+    pub username: Option<String>,
+    pub display_name: Option<String>,
+    pub role: Option<String>,
+    pub locale: Option<String>,
+    pub timezone: Option<String>,
+}
+
+/// Import settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserImportOptions {
+    #[serde(default)]
+    pub format: ImportFormat,
+    /// Roles in the file mapped to site roles, e.g. `{"member": "subscriber"}`
+    #[serde(default)]
+    pub role_map: HashMap<String, String>,
+    /// Role of rows without one
+    #[serde(default = "default_role")]
+    pub default_role: String,
+    #[serde(default)]
+    pub on_duplicate: DuplicatePolicy,
+    /// Email new users a link to set their password
+    #[serde(default)]
+    pub invite: bool,
+    /// Validate and count without writing anything
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+fn default_role() -> String {
+    UserRole::default().to_string()
+}
+
+impl Default for UserImportOptions {
+    fn default() -> Self {
+        Self {
+            format: ImportFormat::default(),
+            role_map: HashMap::new(),
+            default_role: default_role(),
+            on_duplicate: DuplicatePolicy::default(),
+            invite: false,
+            dry_run: false,
+        }
+    }
+}
+
+impl UserImportOptions {
+    /// Check that the role map and default role name site roles, and
+    /// normalize them (`admin` becomes `administrator`)
+    pub fn normalize(mut self) -> Result<Self> {
+        self.default_role = site_role(&self.default_role).ok_or_else(|| {
+            Error::invalid_input(
+                "default_role",
+                format!("Unknown role '{}'", self.default_role),
+            )
+        })?;
+        for (from, to) in self.role_map.iter_mut() {
+            *to = site_role(to).ok_or_else(|| {
+                Error::invalid_input(
+                    "role_map",
+                    format!("'{}' is mapped to unknown role '{}'", from, to),
+                )
+            })?;
+        }
+        Ok(self)
+    }
+
+    /// Site role for the role named in a row
+    pub fn map_role(&self, source: Option<&str>) -> std::result::Result<String, String> {
+        let Some(source) = source.map(str::trim).filter(|s| !s.is_empty()) else {
+            return Ok(self.default_role.clone());
+        };
+        let mapped = self
+            .role_map
+            .iter()
+            .find(|(from, _)| from.trim().eq_ignore_ascii_case(source))
+            .map(|(_, to)| to.as_str())
+            .unwrap_or(source);
+        site_role(mapped)
+            .ok_or_else(|| format!("Unknown role '{}'; add it to the role map", source))
+    }
+}
+
+/// Canonical name of a site role
+fn site_role(name: &str) -> Option<String> {
+    name.trim()
+        .parse::<UserRole>()
+        .ok()
+        .map(|role| role.to_string())
+}
+
+/// Start-import request: settings plus the file contents
+#[derive(Debug, Clone, Deserialize)]
+pub struct UserImportRequest {
+    #[serde(flatten)]
+    pub options: UserImportOptions,
+    pub data: String,
+}
+
+/// A row that could not be imported
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportRowError {
+    /// 1-based position among the file's records
+    pub row: usize,
+    pub email: String,
+    pub message: String,
+}
+
+/// An import run and its progress
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct UserImportRun {
+    pub id: Uuid,
+    pub started_by: Option<Uuid>,
+    /// `running`, `completed` or `failed`
+    pub status: String,
+    pub dry_run: bool,
+    pub total: i32,
+    pub processed: i32,
+    pub created: i32,
+    pub updated: i32,
+    pub skipped: i32,
+    pub failed: i32,
+    pub invited: i32,
+    pub errors: Json<Vec<ImportRowError>>,
+    pub created_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+const RUN_COLUMNS: &str = "id, started_by, status, dry_run, total, processed, created, updated, \
+     skipped, failed, invited, errors, created_at, finished_at";
+
+/// Parse an import file into records
+pub fn parse_records(format: ImportFormat, data: &str) -> Result<Vec<ImportRecord>> {
+    let records = match format {
+        ImportFormat::Csv => parse_csv(data)?,
+        ImportFormat::Json => parse_json(data)?,
+    };
+    if records.is_empty() {
+        return Err(Error::invalid_input("data", "The file contains no users"));
+    }
+    if records.len() > MAX_IMPORT_ROWS {
+        return Err(Error::invalid_input(
+            "data",
+            format!("At most {} users can be imported at once", MAX_IMPORT_ROWS),
+        ));
+    }
+    Ok(records)
+}
+
+fn parse_json(data: &str) -> Result<Vec<ImportRecord>> {
+    let data = data.trim_start_matches('\u{feff}').trim();
+    if data.starts_with('[') {
+        return serde_json::from_str(data)
+            .map_err(|e| Error::invalid_input("data", format!("Invalid JSON: {}", e)));
+    }
+
+    data.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str(line).map_err(|e| {
+                Error::invalid_input("data", format!("Invalid JSON on line {}: {}", index + 1, e))
+            })
+        })
+        .collect()
+}
+
+fn parse_csv(data: &str) -> Result<Vec<ImportRecord>> {
+    let mut rows = csv_rows(data.trim_start_matches('\u{feff}'))?.into_iter();
+    let header: Vec<String> = rows
+        .next()
+        .unwrap_or_default()
+        .iter()
+        .map(|column| column.trim().to_lowercase())
+        .collect();
+    if !header.iter().any(|column| column == "email") {
+        return Err(Error::invalid_input(
+            "data",
+            "The CSV header must have an email column",
+        ));
+    }
+
+    let records = rows
+        .filter(|row| row.iter().any(|field| !field.trim().is_empty()))
+        .map(|row| {
+            let mut record = ImportRecord::default();
+            for (column, value) in header.iter().zip(row) {
+                let value = csv_value(&value);
+                match column.as_str() {
+                    "email" => record.email = value.unwrap_or_default(),
+                    "username" => record.username = value,
+                    "display_name" | "name" => record.display_name = value,
+                    "role" => record.role = value,
+                    "locale" => record.locale = value,
+                    "timezone" => record.timezone = value,
+                    _ => {}
+                }
+            }
+            record
+        })
+        .collect();
+    Ok(records)
+}
+
+/// Trimmed cell value, undoing the export's guard against spreadsheet
+/// formulas (`'=x` is exported for `=x`)
+fn csv_value(value: &str) -> Option<String> {
+    let value = value.trim();
+    let value = match value.strip_prefix('\'') {
+        Some(rest) if rest.starts_with(['=', '+', '-', '@']) => rest,
+        _ => value,
+    };
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+/// Split CSV into rows of fields (RFC 4180: quoted fields may contain
+/// commas, line breaks and doubled quotes)
+fn csv_rows(data: &str) -> Result<Vec<Vec<String>>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = data.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    in_quotes = false;
+                }
+            }
+            '"' if field.is_empty() => in_quotes = true,
+            ',' if !in_quotes => row.push(std::mem::take(&mut field)),
+            '\r' if !in_quotes => {}
+            '\n' if !in_quotes => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            c => field.push(c),
+        }
+    }
+    if in_quotes {
+        return Err(Error::invalid_input(
+            "data",
+            "Unterminated quoted CSV field",
+        ));
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+/// Username derived from an email address when the file has none
+pub fn username_from_email(email: &str) -> String {
+    let local = email.split('@').next().unwrap_or_default();
+    let mut username: String = local
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    username.truncate(40);
+    let username = username.trim_matches('-').to_string();
+    if username.len() < 3 {
+        format!("user-{}", username)
+            .trim_end_matches('-')
+            .to_string()
+    } else {
+        username
+    }
+}
+
+fn is_plausible_email(email: &str) -> bool {
+    match email.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && domain.contains('.')
+                && !domain.starts_with('.')
+                && !domain.ends_with('.')
+                && !email.contains(char::is_whitespace)
+                && !domain.contains('@')
+        }
+        None => false,
+    }
+}
+
+/// Random password for imported users; they set their own through the
+/// invitation or a password reset
+pub(crate) fn generate_password() -> String {
+    let mut bytes = [0u8; 24];
+    OsRng.fill_bytes(&mut bytes);
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Outcome of one imported row
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RowOutcome {
+    Created { invited: bool },
+    Updated,
+    Skipped,
+}
+
+/// Counters of a run while it is processed
+#[derive(Debug, Default)]
+struct ImportProgress {
+    processed: i32,
+    created: i32,
+    updated: i32,
+    skipped: i32,
+    failed: i32,
+    invited: i32,
+    errors: Vec<ImportRowError>,
+}
+
+impl ImportProgress {
+    fn record(&mut self, outcome: RowOutcome) {
+        self.processed += 1;
+        match outcome {
+            RowOutcome::Created { invited } => {
+                self.created += 1;
+                if invited {
+                    self.invited += 1;
+                }
+            }
+            RowOutcome::Updated => self.updated += 1,
+            RowOutcome::Skipped => self.skipped += 1,
+        }
+    }
+
+    fn fail(&mut self, row: usize, email: &str, message: String) {
+        self.processed += 1;
+        self.failed += 1;
+        if self.errors.len() < MAX_REPORTED_ERRORS {
+            self.errors.push(ImportRowError {
+                row,
+                email: email.to_string(),
+                message,
+            });
+        }
+    }
+}
+
+/// [`TokenStore`] over the `password_reset_tokens` table, so links issued
+/// through the [`TokenManager`] are accepted by `/auth/reset-password`
+pub struct PasswordResetTokenStore {
+    pool: PgPool,
+}
+
+impl PasswordResetTokenStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl TokenStore for PasswordResetTokenStore {
+    async fn store_token(&self, token: &SecureToken) -> Result<()> {
+        if token.token_type != SecureTokenType::PasswordReset {
+            return Err(Error::internal(
+                "Only password reset tokens can be stored here",
+            ));
+        }
+        sqlx::query(
+            "INSERT INTO password_reset_tokens (id, user_id, token_hash, expires_at, created_at) \
+             VALUES ($1, $2, $3, $4, $5)",
+        )
+        .bind(token.id)
+        .bind(token.user_id)
+        .bind(&token.token_hash)
+        .bind(token.expires_at)
+        .bind(token.created_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to store token", e))?;
+        Ok(())
+    }
+
+    async fn get_token(
+        &self,
+        token_hash: &str,
+        token_type: SecureTokenType,
+    ) -> Result<Option<SecureToken>> {
+        if token_type != SecureTokenType::PasswordReset {
+            return Ok(None);
+        }
+        let row: Option<(
+            Uuid,
+            Uuid,
+            DateTime<Utc>,
+            Option<DateTime<Utc>>,
+            DateTime<Utc>,
+        )> = sqlx::query_as(
+            "SELECT id, user_id, expires_at, used_at, created_at \
+                 FROM password_reset_tokens WHERE token_hash = $1",
+        )
+        .bind(token_hash)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load token", e))?;
+
+        Ok(row.map(
+            |(id, user_id, expires_at, used_at, created_at)| SecureToken {
+                id,
+                user_id,
+                token_hash: token_hash.to_string(),
+                token_type,
+                expires_at,
+                used_at,
+                created_at,
+                metadata: HashMap::new(),
+            },
+        ))
+    }
+
+    async fn mark_used(&self, id: Uuid) -> Result<()> {
+        sqlx::query("UPDATE password_reset_tokens SET used_at = NOW() WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to update token", e))?;
+        Ok(())
+    }
+
+    async fn invalidate_user_tokens(
+        &self,
+        user_id: Uuid,
+        token_type: SecureTokenType,
+    ) -> Result<u64> {
+        if token_type != SecureTokenType::PasswordReset {
+            return Ok(0);
+        }
+        let result = sqlx::query(
+            "UPDATE password_reset_tokens SET used_at = NOW() \
+             WHERE user_id = $1 AND used_at IS NULL",
+        )
+        .bind(user_id)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to invalidate tokens", e))?;
+        Ok(result.rows_affected())
+    }
+
+    async fn cleanup_expired(&self) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM password_reset_tokens WHERE expires_at < NOW()")
+            .execute(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to delete tokens", e))?;
+        Ok(result.rows_affected())
+    }
+}
+
+/// Bulk user imports
+pub struct UserImportService {
+    pool: PgPool,
+    email: Arc<EmailService>,
+    invitations: TokenManager<PasswordResetTokenStore>,
+}
+
+impl UserImportService {
+    pub fn new(pool: PgPool, email: Arc<EmailService>) -> Self {
+        let invitations = TokenManager::new(
+            PasswordResetTokenStore::new(pool.clone()),
+            TokenConfig {
+                password_reset_duration: Duration::days(INVITATION_DAYS),
+                ..TokenConfig::default()
+            },
+        );
+        Self {
+            pool,
+            email,
+            invitations,
+        }
+    }
+
+    /// Validate an import and start processing it in the background
+    pub async fn start(
+        self: &Arc<Self>,
+        started_by: Uuid,
+        request: UserImportRequest,
+    ) -> Result<UserImportRun> {
+        let options = request.options.normalize()?;
+        let records = parse_records(options.format, &request.data)?;
+
+        let run: UserImportRun = sqlx::query_as(&format!(
+            "INSERT INTO user_imports (id, started_by, dry_run, total) \
+             VALUES ($1, $2, $3, $4) RETURNING {}",
+            RUN_COLUMNS
+        ))
+        .bind(Uuid::now_v7())
+        .bind(started_by)
+        .bind(options.dry_run)
+        .bind(records.len() as i32)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to start user import", e))?;
+
+        let service = self.clone();
+        let run_id = run.id;
+        tokio::spawn(async move {
+            let worker = service.clone();
+            let task = tokio::spawn(async move { worker.process(run_id, records, options).await });
+            // Don't leave the run looking busy forever when processing dies
+            if task.await.is_err() {
+                tracing::error!(import_id = %run_id, "User import aborted");
+                let result = sqlx::query(
+                    "UPDATE user_imports SET status = 'failed', finished_at = NOW() WHERE id = $1",
+                )
+                .bind(run_id)
+                .execute(&service.pool)
+                .await;
+                if let Err(e) = result {
+                    tracing::error!(import_id = %run_id, "Failed to mark user import failed: {}", e);
+                }
+            }
+        });
+        Ok(run)
+    }
+
+    /// An import run
+    pub async fn get(&self, id: Uuid) -> Result<UserImportRun> {
+        sqlx::query_as(&format!(
+            "SELECT {} FROM user_imports WHERE id = $1",
+            RUN_COLUMNS
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load user import", e))?
+        .ok_or_else(|| Error::not_found("User import", id.to_string()))
+    }
+
+    /// Most recent import runs
+    pub async fn list(&self, limit: i64) -> Result<Vec<UserImportRun>> {
+        sqlx::query_as(&format!(
+            "SELECT {} FROM user_imports ORDER BY created_at DESC LIMIT $1",
+            RUN_COLUMNS
+        ))
+        .bind(limit.clamp(1, 100))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to list user imports", e))
+    }
+
+    async fn process(&self, run_id: Uuid, records: Vec<ImportRecord>, options: UserImportOptions) {
+        let users = UserService::new(self.pool.clone());
+        let mut progress = ImportProgress::default();
+        let mut seen_emails = HashSet::new();
+        let mut taken_usernames = HashSet::new();
+
+        for (index, record) in records.iter().enumerate() {
+            let row = index + 1;
+            match self
+                .import_row(
+                    &users,
+                    record,
+                    &options,
+                    &mut seen_emails,
+                    &mut taken_usernames,
+                )
+                .await
+            {
+                Ok(outcome) => progress.record(outcome),
+                Err(message) => progress.fail(row, record.email.trim(), message),
+            }
+
+            if row % PROGRESS_INTERVAL == 0 {
+                self.save_progress(run_id, &progress, None).await;
+            }
+        }
+
+        tracing::info!(
+            import_id = %run_id,
+            created = progress.created,
+            updated = progress.updated,
+            skipped = progress.skipped,
+            failed = progress.failed,
+            dry_run = options.dry_run,
+            "User import finished"
+        );
+        self.save_progress(run_id, &progress, Some("completed"))
+            .await;
+    }
+
+    async fn import_row(
+        &self,
+        users: &UserService,
+        record: &ImportRecord,
+        options: &UserImportOptions,
+        seen_emails: &mut HashSet<String>,
+        taken_usernames: &mut HashSet<String>,
+    ) -> std::result::Result<RowOutcome, String> {
+        let email = record.email.trim().to_lowercase();
+        if !is_plausible_email(&email) {
+            return Err("Invalid email address".to_string());
+        }
+        // Later rows with an email seen before are duplicates
+        if !seen_emails.insert(email.clone()) {
+            return Ok(RowOutcome::Skipped);
+        }
+        let role = options.map_role(record.role.as_deref())?;
+
+        let existing = users
+            .get_user_by_email(&email)
+            .await
+            .map_err(|e| e.to_string())?;
+        if let Some(existing) = existing {
+            if options.on_duplicate == DuplicatePolicy::Skip {
+                return Ok(RowOutcome::Skipped);
+            }
+            if !options.dry_run {
+                let update = UpdateUserRequest {
+                    email: None,
+                    username: None,
+                    display_name: record.display_name.clone(),
+                    status: None,
+                    role: Some(role),
+                    avatar_url: None,
+                    locale: record.locale.clone(),
+                    timezone: record.timezone.clone(),
+                };
+                users
+                    .update_user(existing.id, update)
+                    .await
+                    .map_err(|e| e.to_string())?;
+            }
+            return Ok(RowOutcome::Updated);
+        }
+
+        let username = match record.username.as_deref().map(str::trim) {
+            Some(username) if !username.is_empty() => {
+                let taken = taken_usernames.contains(&username.to_lowercase())
+                    || users
+                        .get_user_by_username(username)
+                        .await
+                        .map_err(|e| e.to_string())?
+                        .is_some();
+                if taken {
+                    return Err(format!("Username '{}' is already taken", username));
+                }
+                username.to_string()
+            }
+            _ => {
+                self.free_username(users, &username_from_email(&email), taken_usernames)
+                    .await?
+            }
+        };
+        taken_usernames.insert(username.to_lowercase());
+
+        if options.dry_run {
+            return Ok(RowOutcome::Created {
+                invited: options.invite,
+            });
+        }
+
+        let created = users
+            .create_user(CreateUserRequest {
+                email,
+                username,
+                password: generate_password(),
+                display_name: record.display_name.clone(),
+                role: Some(role),
+                locale: record.locale.clone(),
+                timezone: record.timezone.clone(),
+            })
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let invited = options.invite && self.invite(&created).await;
+        Ok(RowOutcome::Created { invited })
+    }
+
+    /// First of `base`, `base-2`, `base-3`… not used by anyone
+    async fn free_username(
+        &self,
+        users: &UserService,
+        base: &str,
+        taken_usernames: &HashSet<String>,
+    ) -> std::result::Result<String, String> {
+        for n in 1..=100 {
+            let candidate = if n == 1 {
+                base.to_string()
+            } else {
+                format!("{}-{}", base, n)
+            };
+            if taken_usernames.contains(&candidate) {
+                continue;
+            }
+            let exists = users
+                .get_user_by_username(&candidate)
+                .await
+                .map_err(|e| e.to_string())?
+                .is_some();
+            if !exists {
+                return Ok(candidate);
+            }
+        }
+        Err(format!("No free username found for '{}'", base))
+    }
+
+    /// Email a set-password link; returns whether it was sent
+    async fn invite(&self, user: &UserResponse) -> bool {
+        if !self.email.is_enabled().await {
+            tracing::warn!(
+                user_id = %user.id,
+                "Email service not enabled; imported user was not invited"
+            );
+            return false;
+        }
+
+        let token = match self
+            .invitations
+            .create_password_reset(user.id, &user.email, None, None)
+            .await
+        {
+            Ok((token, _)) => token,
+            Err(e) => {
+                tracing::error!(user_id = %user.id, "Failed to create invitation token: {}", e);
+                return false;
+            }
+        };
+
+        match self
+            .email
+            .send_invitation(&user.email, user.display_name.as_deref(), &token)
+            .await
+        {
+            Ok(result) => result.success,
+            Err(e) => {
+                tracing::error!(user_id = %user.id, "Failed to send invitation: {}", e);
+                false
+            }
+        }
+    }
+
+    async fn save_progress(&self, run_id: Uuid, progress: &ImportProgress, status: Option<&str>) {
+        let result = sqlx::query(
+            "UPDATE user_imports SET processed = $2, created = $3, updated = $4, skipped = $5, \
+             failed = $6, invited = $7, errors = $8, status = COALESCE($9, status), \
+             finished_at = CASE WHEN $9 IS NULL THEN finished_at ELSE NOW() END \
+             WHERE id = $1",
+        )
+        .bind(run_id)
+        .bind(progress.processed)
+        .bind(progress.created)
+        .bind(progress.updated)
+        .bind(progress.skipped)
+        .bind(progress.failed)
+        .bind(progress.invited)
+        .bind(Json(&progress.errors))
+        .bind(status)
+        .execute(&self.pool)
+        .await;
+        if let Err(e) = result {
+            tracing::error!(import_id = %run_id, "Failed to save user import progress: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_csv() {
+        let data = "\u{feff}Email,Username,Name,Role,Password\r\n\
+                    ada@example.com,ada,\"Lovelace, Ada\",Member,secret\r\n\
+                    \r\n\
+                    bob@example.com,,\"Bob \"\"The Builder\"\"\nJr\",,\n\
+                    carl@example.com,carl,'-Carl,,";
+        let records = parse_records(ImportFormat::Csv, data).unwrap();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].display_name.as_deref(), Some("Lovelace, Ada"));
+        assert_eq!(records[0].role.as_deref(), Some("Member"));
+        assert_eq!(records[1].username, None);
+        assert_eq!(
+            records[1].display_name.as_deref(),
+            Some("Bob \"The Builder\"\nJr")
+        );
+        assert_eq!(records[2].display_name.as_deref(), Some("-Carl"));
+
+        assert!(parse_records(ImportFormat::Csv, "username\nada").is_err());
+        assert!(parse_records(ImportFormat::Csv, "email\n\"ada@example.com").is_err());
+        assert!(parse_records(ImportFormat::Csv, "email\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_json() {
+        let array = r#"[{"email": "ada@example.com", "role": "editor", "id": "x"}]"#;
+        let records = parse_records(ImportFormat::Json, array).unwrap();
+        assert_eq!(records[0].role.as_deref(), Some("editor"));
+
+        let lines = "{\"email\": \"ada@example.com\"}\n\n{\"email\": \"bob@example.com\"}\n";
+        assert_eq!(parse_records(ImportFormat::Json, lines).unwrap().len(), 2);
+
+        let err = parse_records(ImportFormat::Json, "{\"email\": 1}").unwrap_err();
+        assert!(err.to_string().contains("line 1"));
+    }
+
+    #[test]
+    fn test_role_mapping() {
+        let options = UserImportOptions {
+            role_map: HashMap::from([
+                ("Member".to_string(), "subscriber".to_string()),
+                ("staff".to_string(), "admin".to_string()),
+            ]),
+            default_role: "Author".to_string(),
+            ..Default::default()
+        }
+        .normalize()
+        .unwrap();
+
+        assert_eq!(options.default_role, "author");
+        assert_eq!(options.map_role(None).unwrap(), "author");
+        assert_eq!(options.map_role(Some("  ")).unwrap(), "author");
+        assert_eq!(options.map_role(Some("member")).unwrap(), "subscriber");
+        assert_eq!(options.map_role(Some("STAFF")).unwrap(), "administrator");
+        assert_eq!(options.map_role(Some("editor")).unwrap(), "editor");
+        assert!(options.map_role(Some("owner")).is_err());
+
+        let bad = UserImportOptions {
+            role_map: HashMap::from([("member".to_string(), "owner".to_string())]),
+            ..Default::default()
+        };
+        assert!(bad.normalize().is_err());
+    }
+
+    #[test]
+    fn test_usernames_and_progress() {
+        assert_eq!(
+            username_from_email("Ada.Lovelace@example.com"),
+            "ada-lovelace"
+        );
+        assert_eq!(username_from_email("jo@example.com"), "user-jo");
+        assert_eq!(username_from_email("+@example.com"), "user");
+        assert!(is_plausible_email("ada@example.com"));
+        assert!(!is_plausible_email("ada@localhost"));
+        assert!(!is_plausible_email("ada example@example.com"));
+
+        let mut progress = ImportProgress::default();
+        progress.record(RowOutcome::Created { invited: true });
+        progress.record(RowOutcome::Skipped);
+        for row in 0..MAX_REPORTED_ERRORS + 5 {
+            progress.fail(row, "x@example.com", "bad".to_string());
+        }
+        assert_eq!(progress.processed, MAX_REPORTED_ERRORS as i32 + 7);
+        assert_eq!(progress.failed, MAX_REPORTED_ERRORS as i32 + 5);
+        assert_eq!(progress.errors.len(), MAX_REPORTED_ERRORS);
+        assert_eq!((progress.created, progress.invited), (1, 1));
+    }
+}
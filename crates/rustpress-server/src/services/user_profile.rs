@@ -0,0 +1,891 @@
+//! User Profile Fields
+//!
+//! Extensible profile fields (bio, website, social links and any fields an
+//! administrator registers) with a visibility per field, plus the choice
+//! of avatar source. Avatar images themselves are served by
+//! [`AvatarService`](super::avatar::AvatarService).
+//!
+//! `bio` and `website` are stored in their `users` columns, which public
+//! author pages already read; every other field lives in `users.meta`
+//! under `profile`, with per-user visibility overrides under
+//! `profile_visibility` and the avatar choice under `avatar`.
+
+use rustpress_core::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sqlx::{FromRow, PgPool};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use super::avatar::{avatar_url_for, avatar_version};
+use super::json_setting::JsonSetting;
+use super::regions::inject_head_tags;
+
+/// Settings key holding the profile field definitions
+pub const PROFILE_FIELDS_SETTINGS_KEY: &str = "profile_fields";
+
+/// Stored profile fields
+const PROFILE_FIELDS_SETTING: JsonSetting<ProfileFieldsConfig> =
+    JsonSetting::new(PROFILE_FIELDS_SETTINGS_KEY, "users", "profile fields");
+
+/// Fields stored in their own `users` column rather than in meta
+const COLUMN_FIELDS: [&str; 2] = ["bio", "website"];
+
+/// Avatar size used in profile views and structured data
+const PROFILE_AVATAR_SIZE: u32 = 96;
+
+/// Kind of value a profile field holds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProfileFieldType {
+    #[default]
+    Text,
+    Textarea,
+    Url,
+    /// A link to a profile on another site, listed with the social links
+    SocialLink,
+}
+
+/// Who can see a profile field
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldVisibility {
+    /// Anyone, including public author pages
+    #[default]
+    Public,
+    /// Signed-in users
+    Members,
+    /// The user and administrators
+    Private,
+}
+
+/// A registered profile field
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProfileFieldDefinition {
+    /// Storage key, lowercase letters, digits and underscores
+    pub key: String,
+    pub label: String,
+    #[serde(default)]
+    pub field_type: ProfileFieldType,
+    #[serde(default = "default_max_length")]
+    pub max_length: usize,
+    #[serde(default)]
+    pub default_visibility: FieldVisibility,
+    /// Whether users may choose a different visibility
+    #[serde(default = "default_true")]
+    pub user_visibility: bool,
+    /// Expands a bare handle into a URL, e.g. `https://github.com/{}`
+    #[serde(default)]
+    pub url_template: Option<String>,
+}
+
+fn default_max_length() -> usize {
+    255
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl ProfileFieldDefinition {
+    fn new(key: &str, label: &str, field_type: ProfileFieldType) -> Self {
+        Self {
+            key: key.to_string(),
+            label: label.to_string(),
+            field_type,
+            max_length: default_max_length(),
+            default_visibility: FieldVisibility::Public,
+            user_visibility: true,
+            url_template: None,
+        }
+    }
+
+    fn social(key: &str, label: &str, url_template: &str) -> Self {
+        Self {
+            url_template: Some(url_template.to_string()),
+            ..Self::new(key, label, ProfileFieldType::SocialLink)
+        }
+    }
+
+    /// Validate and normalize a submitted value
+    fn normalize(&self, value: &str) -> Result<String> {
+        let value = value.trim();
+        if value.chars().count() > self.max_length {
+            return Err(Error::invalid_input(
+                &self.key,
+                format!("Must be at most {} characters", self.max_length),
+            ));
+        }
+
+        match self.field_type {
+            ProfileFieldType::Text | ProfileFieldType::Textarea => Ok(value.to_string()),
+            ProfileFieldType::Url | ProfileFieldType::SocialLink => {
+                let expanded = match &self.url_template {
+                    Some(template) if !value.contains("://") => {
+                        template.replace("{}", value.trim_start_matches('@'))
+                    }
+                    _ => value.to_string(),
+                };
+                match reqwest::Url::parse(&expanded) {
+                    Ok(url) if matches!(url.scheme(), "http" | "https") => Ok(url.to_string()),
+                    _ => Err(Error::invalid_input(&self.key, "Must be an http(s) URL")),
+                }
+            }
+        }
+    }
+}
+
+/// Gravatar proxy settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GravatarSettings {
+    pub enabled: bool,
+    /// Image for addresses without a Gravatar: `initials` for the locally
+    /// generated fallback, or a Gravatar default (`mp`, `identicon`, ...)
+    pub default_image: String,
+    /// Highest rating served (`g`, `pg`, `r`, `x`)
+    pub rating: String,
+}
+
+impl Default for GravatarSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            default_image: "initials".to_string(),
+            rating: "g".to_string(),
+        }
+    }
+}
+
+/// Registered profile fields and avatar settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProfileFieldsConfig {
+    /// Fields in display order
+    pub fields: Vec<ProfileFieldDefinition>,
+    pub gravatar: GravatarSettings,
+}
+
+impl Default for ProfileFieldsConfig {
+    fn default() -> Self {
+        Self {
+            fields: vec![
+                ProfileFieldDefinition {
+                    max_length: 2000,
+                    ..ProfileFieldDefinition::new("bio", "Biography", ProfileFieldType::Textarea)
+                },
+                ProfileFieldDefinition::new("website", "Website", ProfileFieldType::Url),
+                ProfileFieldDefinition::new("job_title", "Job title", ProfileFieldType::Text),
+                ProfileFieldDefinition {
+                    max_length: 40,
+                    ..ProfileFieldDefinition::new("pronouns", "Pronouns", ProfileFieldType::Text)
+                },
+                ProfileFieldDefinition {
+                    default_visibility: FieldVisibility::Members,
+                    ..ProfileFieldDefinition::new("location", "Location", ProfileFieldType::Text)
+                },
+                ProfileFieldDefinition::social("twitter", "X (Twitter)", "https://x.com/{}"),
+                ProfileFieldDefinition::new("mastodon", "Mastodon", ProfileFieldType::SocialLink),
+                ProfileFieldDefinition::social("github", "GitHub", "https://github.com/{}"),
+                ProfileFieldDefinition::social(
+                    "linkedin",
+                    "LinkedIn",
+                    "https://www.linkedin.com/in/{}",
+                ),
+                ProfileFieldDefinition::social(
+                    "instagram",
+                    "Instagram",
+                    "https://www.instagram.com/{}",
+                ),
+                ProfileFieldDefinition::social("youtube", "YouTube", "https://www.youtube.com/@{}"),
+            ],
+            gravatar: GravatarSettings::default(),
+        }
+    }
+}
+
+impl ProfileFieldsConfig {
+    /// Load the configuration from settings, falling back to defaults
+    pub async fn load(pool: &PgPool) -> Result<Self> {
+        PROFILE_FIELDS_SETTING.load_or_default(pool).await
+    }
+
+    /// Persist the configuration to settings
+    pub async fn save(&self, pool: &PgPool) -> Result<()> {
+        PROFILE_FIELDS_SETTING.save(pool, self).await
+    }
+
+    /// Check field keys, labels and templates
+    pub fn validate(&self) -> Result<()> {
+        let mut seen = std::collections::HashSet::new();
+        for field in &self.fields {
+            let valid_key = !field.key.is_empty()
+                && field
+                    .key
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+            if !valid_key {
+                return Err(Error::invalid_input(
+                    "fields",
+                    format!("Invalid field key '{}'", field.key),
+                ));
+            }
+            if !seen.insert(field.key.as_str()) {
+                return Err(Error::invalid_input(
+                    "fields",
+                    format!("Duplicate field key '{}'", field.key),
+                ));
+            }
+            if field.label.trim().is_empty() || field.max_length == 0 {
+                return Err(Error::invalid_input(
+                    "fields",
+                    format!("Field '{}' needs a label and a maximum length", field.key),
+                ));
+            }
+            if field
+                .url_template
+                .as_ref()
+                .is_some_and(|t| !t.contains("{}"))
+            {
+                return Err(Error::invalid_input(
+                    "fields",
+                    format!("URL template of '{}' must contain {{}}", field.key),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn field(&self, key: &str) -> Option<&ProfileFieldDefinition> {
+        self.fields.iter().find(|f| f.key == key)
+    }
+}
+
+/// Where a user's avatar comes from
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "source", rename_all = "snake_case")]
+pub enum AvatarSource {
+    /// Gravatar, served through the proxy
+    #[default]
+    Gravatar,
+    /// An image from the media library
+    Media { media_id: Uuid },
+    /// An image uploaded through the avatar endpoint
+    Upload { path: String },
+    /// Generated initials
+    None,
+}
+
+/// Who is looking at a profile
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProfileViewer {
+    Anonymous,
+    Member,
+    /// The profile owner or an administrator
+    Owner,
+}
+
+impl ProfileViewer {
+    pub fn for_user(viewer: Option<(Uuid, bool)>, profile_user_id: Uuid) -> Self {
+        match viewer {
+            Some((id, is_admin)) if is_admin || id == profile_user_id => Self::Owner,
+            Some(_) => Self::Member,
+            None => Self::Anonymous,
+        }
+    }
+
+    fn can_see(self, visibility: FieldVisibility) -> bool {
+        match self {
+            Self::Owner => true,
+            Self::Member => visibility != FieldVisibility::Private,
+            Self::Anonymous => visibility == FieldVisibility::Public,
+        }
+    }
+}
+
+/// A profile field value as shown to a viewer
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProfileFieldValue {
+    pub key: String,
+    pub label: String,
+    pub field_type: ProfileFieldType,
+    pub value: String,
+    pub visibility: FieldVisibility,
+}
+
+/// A link to another site, for `rel="me"` lists and `sameAs`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SocialLink {
+    pub network: String,
+    pub label: String,
+    pub url: String,
+}
+
+/// A user's profile filtered for one viewer
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProfileView {
+    pub user_id: Uuid,
+    pub username: String,
+    pub display_name: String,
+    pub avatar_url: Option<String>,
+    /// Public author page
+    pub url: String,
+    /// Visible field values by key
+    pub values: BTreeMap<String, String>,
+    /// Visible fields in display order
+    pub fields: Vec<ProfileFieldValue>,
+    pub social_links: Vec<SocialLink>,
+    /// Avatar choice; only shown to the owner
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avatar: Option<AvatarSource>,
+}
+
+/// Changes to a profile; unspecified fields are left untouched
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ProfileUpdate {
+    /// New values; `null` or an empty string clears a field
+    pub fields: HashMap<String, Option<String>>,
+    pub visibility: HashMap<String, FieldVisibility>,
+    pub avatar: Option<AvatarSource>,
+}
+
+const PROFILE_SELECT: &str =
+    "SELECT id, username, email, display_name, avatar_url, bio, website, meta FROM users";
+
+#[derive(Debug, FromRow)]
+struct ProfileRow {
+    id: Uuid,
+    username: String,
+    email: String,
+    display_name: Option<String>,
+    avatar_url: Option<String>,
+    bio: Option<String>,
+    website: Option<String>,
+    meta: Option<Value>,
+}
+
+/// Stored profile data of one user
+#[derive(Debug, Clone, Default, PartialEq)]
+struct StoredProfile {
+    values: BTreeMap<String, String>,
+    visibility: BTreeMap<String, FieldVisibility>,
+    avatar: AvatarSource,
+}
+
+impl StoredProfile {
+    fn from_row(row: &ProfileRow) -> Self {
+        let meta = row.meta.as_ref();
+        let section = |key: &str| meta.and_then(|m| m.get(key)).cloned();
+
+        let mut values: BTreeMap<String, String> = section("profile")
+            .and_then(|v| serde_json::from_value(v).ok())
+            .unwrap_or_default();
+        for (key, column) in [("bio", &row.bio), ("website", &row.website)] {
+            match column.as_deref().filter(|v| !v.is_empty()) {
+                Some(value) => values.insert(key.to_string(), value.to_string()),
+                None => values.remove(key),
+            };
+        }
+
+        Self {
+            values,
+            visibility: section("profile_visibility")
+                .and_then(|v| serde_json::from_value(v).ok())
+                .unwrap_or_default(),
+            avatar: section("avatar")
+                .and_then(|v| serde_json::from_value(v).ok())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Apply an update, validating values against the field definitions
+    fn apply(&mut self, config: &ProfileFieldsConfig, update: &ProfileUpdate) -> Result<()> {
+        for (key, value) in &update.fields {
+            let field = config
+                .field(key)
+                .ok_or_else(|| Error::invalid_input(key, "Unknown profile field"))?;
+            match value.as_deref().map(str::trim).filter(|v| !v.is_empty()) {
+                Some(value) => {
+                    self.values.insert(key.clone(), field.normalize(value)?);
+                }
+                None => {
+                    self.values.remove(key);
+                }
+            }
+        }
+
+        for (key, visibility) in &update.visibility {
+            let field = config
+                .field(key)
+                .ok_or_else(|| Error::invalid_input(key, "Unknown profile field"))?;
+            if !field.user_visibility && *visibility != field.default_visibility {
+                return Err(Error::invalid_input(
+                    key,
+                    "Visibility of this field is fixed",
+                ));
+            }
+            self.visibility.insert(key.clone(), *visibility);
+        }
+
+        if let Some(ref avatar) = update.avatar {
+            // Uploads are only set by the avatar endpoint, never by path
+            if matches!(avatar, AvatarSource::Upload { .. }) && *avatar != self.avatar {
+                return Err(Error::invalid_input(
+                    "avatar",
+                    "Upload a new avatar through the avatar endpoint",
+                ));
+            }
+            self.avatar = avatar.clone();
+        }
+        Ok(())
+    }
+
+    fn visibility_of(&self, field: &ProfileFieldDefinition) -> FieldVisibility {
+        if !field.user_visibility {
+            return field.default_visibility;
+        }
+        self.visibility
+            .get(&field.key)
+            .copied()
+            .unwrap_or(field.default_visibility)
+    }
+
+    fn view(
+        &self,
+        row: &ProfileRow,
+        config: &ProfileFieldsConfig,
+        viewer: ProfileViewer,
+    ) -> ProfileView {
+        let mut view = ProfileView {
+            user_id: row.id,
+            username: row.username.clone(),
+            display_name: row
+                .display_name
+                .clone()
+                .filter(|n| !n.trim().is_empty())
+                .unwrap_or_else(|| row.username.clone()),
+            avatar_url: Some(avatar_url_for(
+                row.id,
+                row.avatar_url.as_deref(),
+                PROFILE_AVATAR_SIZE,
+            )),
+            url: format!("/author/{}", row.username),
+            values: BTreeMap::new(),
+            fields: Vec::new(),
+            social_links: Vec::new(),
+            avatar: (viewer == ProfileViewer::Owner).then(|| self.avatar.clone()),
+        };
+
+        for field in &config.fields {
+            let Some(value) = self.values.get(&field.key) else {
+                continue;
+            };
+            let visibility = self.visibility_of(field);
+            if !viewer.can_see(visibility) {
+                continue;
+            }
+            if field.field_type == ProfileFieldType::SocialLink {
+                view.social_links.push(SocialLink {
+                    network: field.key.clone(),
+                    label: field.label.clone(),
+                    url: value.clone(),
+                });
+            }
+            view.values.insert(field.key.clone(), value.clone());
+            view.fields.push(ProfileFieldValue {
+                key: field.key.clone(),
+                label: field.label.clone(),
+                field_type: field.field_type,
+                value: value.clone(),
+                visibility,
+            });
+        }
+        view
+    }
+}
+
+impl ProfileView {
+    /// schema.org `ProfilePage` describing the user as a `Person`
+    pub fn structured_data(&self, site_url: &str) -> Value {
+        let site_url = site_url.trim_end_matches('/');
+        let absolute = |url: &str| {
+            if url.starts_with('/') {
+                format!("{}{}", site_url, url)
+            } else {
+                url.to_string()
+            }
+        };
+
+        let mut same_as: Vec<String> = self.social_links.iter().map(|l| l.url.clone()).collect();
+        if let Some(website) = self.values.get("website") {
+            same_as.insert(0, website.clone());
+        }
+
+        let mut person = json!({
+            "@type": "Person",
+            "name": self.display_name,
+            "alternateName": self.username,
+            "url": absolute(&self.url),
+        });
+        if let Some(bio) = self.values.get("bio") {
+            person["description"] = json!(bio);
+        }
+        if let Some(ref avatar) = self.avatar_url {
+            person["image"] = json!(absolute(avatar));
+        }
+        if let Some(job_title) = self.values.get("job_title") {
+            person["jobTitle"] = json!(job_title);
+        }
+        if !same_as.is_empty() {
+            person["sameAs"] = json!(same_as);
+        }
+
+        json!({
+            "@context": "https://schema.org",
+            "@type": "ProfilePage",
+            "url": absolute(&self.url),
+            "mainEntity": person,
+        })
+    }
+}
+
+/// Add a JSON-LD script to the `<head>` of a page
+pub fn inject_json_ld(html: &str, data: &Value) -> String {
+    // `</` must not appear inside a script element
+    let tag = format!(
+        "<script type=\"application/ld+json\">{}</script>",
+        data.to_string().replace("</", "<\\/")
+    );
+    inject_head_tags(html, &tag)
+}
+
+/// What the avatar service needs to know about a user
+#[derive(Debug, Clone)]
+pub struct AvatarSubject {
+    pub user_id: Uuid,
+    pub display_name: String,
+    pub email: String,
+    pub source: AvatarSource,
+}
+
+/// Profile fields and avatar choice
+pub struct ProfileService {
+    pool: PgPool,
+    config: RwLock<Option<Arc<ProfileFieldsConfig>>>,
+}
+
+impl ProfileService {
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            config: RwLock::new(None),
+        }
+    }
+
+    /// Field definitions, loaded from settings on first use
+    pub async fn config(&self) -> Arc<ProfileFieldsConfig> {
+        if let Some(config) = self.config.read().await.clone() {
+            return config;
+        }
+
+        let config = ProfileFieldsConfig::load(&self.pool)
+            .await
+            .unwrap_or_else(|e| {
+                tracing::warn!("Failed to load profile fields, using defaults: {}", e);
+                ProfileFieldsConfig::default()
+            });
+        let config = Arc::new(config);
+        *self.config.write().await = Some(config.clone());
+        config
+    }
+
+    /// Validate, persist and apply new field definitions
+    pub async fn set_config(&self, config: ProfileFieldsConfig) -> Result<()> {
+        config.validate()?;
+        config.save(&self.pool).await?;
+        *self.config.write().await = Some(Arc::new(config));
+        Ok(())
+    }
+
+    /// Drop the cached field definitions; the next use reloads them
+    pub async fn invalidate(&self) {
+        *self.config.write().await = None;
+    }
+
+    async fn load_row_by_id(&self, user_id: Uuid) -> Result<Option<ProfileRow>> {
+        sqlx::query_as::<_, ProfileRow>(&format!(
+            "{} WHERE id = $1 AND deleted_at IS NULL",
+            PROFILE_SELECT
+        ))
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load profile", e))
+    }
+
+    async fn load_row_by_username(&self, username: &str) -> Result<Option<ProfileRow>> {
+        sqlx::query_as::<_, ProfileRow>(&format!(
+            "{} WHERE username = $1 AND deleted_at IS NULL",
+            PROFILE_SELECT
+        ))
+        .bind(username)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load profile", e))
+    }
+
+    /// Profile of a user as `viewer` sees it
+    pub async fn view(&self, user_id: Uuid, viewer: ProfileViewer) -> Result<Option<ProfileView>> {
+        let Some(row) = self.load_row_by_id(user_id).await? else {
+            return Ok(None);
+        };
+        let config = self.config().await;
+        Ok(Some(
+            StoredProfile::from_row(&row).view(&row, &config, viewer),
+        ))
+    }
+
+    /// Public profile of an author by username
+    pub async fn view_by_username(
+        &self,
+        username: &str,
+        viewer: ProfileViewer,
+    ) -> Result<Option<ProfileView>> {
+        let Some(row) = self.load_row_by_username(username).await? else {
+            return Ok(None);
+        };
+        let config = self.config().await;
+        Ok(Some(
+            StoredProfile::from_row(&row).view(&row, &config, viewer),
+        ))
+    }
+
+    /// Apply a profile update and return the owner's view of the result
+    pub async fn update(&self, user_id: Uuid, update: ProfileUpdate) -> Result<ProfileView> {
+        let row = self
+            .load_row_by_id(user_id)
+            .await?
+            .ok_or_else(|| Error::not_found("User", user_id.to_string()))?;
+        let config = self.config().await;
+
+        let mut profile = StoredProfile::from_row(&row);
+        profile.apply(&config, &update)?;
+        self.save(row, profile, &config).await
+    }
+
+    /// Replace a user's avatar source, including uploads
+    pub(crate) async fn set_avatar(
+        &self,
+        user_id: Uuid,
+        source: AvatarSource,
+    ) -> Result<(AvatarSource, ProfileView)> {
+        let row = self
+            .load_row_by_id(user_id)
+            .await?
+            .ok_or_else(|| Error::not_found("User", user_id.to_string()))?;
+        let config = self.config().await;
+
+        let mut profile = StoredProfile::from_row(&row);
+        let previous = std::mem::replace(&mut profile.avatar, source);
+        Ok((previous, self.save(row, profile, &config).await?))
+    }
+
+    /// Name, email and avatar source of a user
+    pub(crate) async fn avatar_subject(&self, user_id: Uuid) -> Result<Option<AvatarSubject>> {
+        let Some(row) = self.load_row_by_id(user_id).await? else {
+            return Ok(None);
+        };
+        let source = StoredProfile::from_row(&row).avatar;
+        Ok(Some(AvatarSubject {
+            user_id,
+            display_name: row
+                .display_name
+                .filter(|n| !n.trim().is_empty())
+                .unwrap_or(row.username),
+            email: row.email,
+            source,
+        }))
+    }
+
+    async fn save(
+        &self,
+        row: ProfileRow,
+        profile: StoredProfile,
+        config: &ProfileFieldsConfig,
+    ) -> Result<ProfileView> {
+        let user_id = row.id;
+        let previous = StoredProfile::from_row(&row).avatar;
+        let avatar_url = match profile.avatar {
+            AvatarSource::Media { media_id } => Some(self.media_avatar_url(media_id).await?),
+            // A new version busts browser caches of `/avatar/{id}`
+            ref source if *source != previous || row.avatar_url.is_none() => {
+                Some(format!("/avatar/{}?v={}", user_id, avatar_version()))
+            }
+            _ => row.avatar_url.clone(),
+        };
+
+        let meta_values: BTreeMap<&String, &String> = profile
+            .values
+            .iter()
+            .filter(|(key, _)| !COLUMN_FIELDS.contains(&key.as_str()))
+            .collect();
+        let meta = json!({
+            "profile": meta_values,
+            "profile_visibility": profile.visibility,
+            "avatar": profile.avatar,
+        });
+
+        sqlx::query(
+            r#"
+            UPDATE users
+            SET bio = $2, website = $3, avatar_url = $4,
+                meta = COALESCE(meta, '{}'::jsonb) || $5, updated_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(user_id)
+        .bind(profile.values.get("bio"))
+        .bind(profile.values.get("website"))
+        .bind(&avatar_url)
+        .bind(&meta)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to save profile", e))?;
+
+        let row = ProfileRow { avatar_url, ..row };
+        Ok(profile.view(&row, config, ProfileViewer::Owner))
+    }
+
+    pub(crate) async fn media_avatar_url(&self, media_id: Uuid) -> Result<String> {
+        let row: Option<(String, String)> =
+            sqlx::query_as("SELECT storage_path, mime_type FROM media WHERE id = $1")
+                .bind(media_id)
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(|e| Error::database_with_source("Failed to load avatar media", e))?;
+
+        match row {
+            Some((path, mime)) if mime.starts_with("image/") => Ok(format!("/uploads/{}", path)),
+            Some(_) => Err(Error::invalid_input("avatar", "Avatar must be an image")),
+            None => Err(Error::not_found("Media", media_id.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(meta: Value) -> ProfileRow {
+        ProfileRow {
+            id: Uuid::nil(),
+            username: "jdoe".to_string(),
+            email: "JDoe@Example.com ".to_string(),
+            display_name: Some("J. Doe".to_string()),
+            avatar_url: Some("/avatar/00000000-0000-0000-0000-000000000000".to_string()),
+            bio: Some("Writes about </script> things".to_string()),
+            website: None,
+            meta: Some(meta),
+        }
+    }
+
+    #[test]
+    fn test_update_validates_and_expands_fields() {
+        let config = ProfileFieldsConfig::default();
+        let mut profile = StoredProfile::default();
+
+        let update = ProfileUpdate {
+            fields: HashMap::from([
+                ("github".to_string(), Some("@jdoe".to_string())),
+                ("website".to_string(), Some("https://jdoe.dev".to_string())),
+                ("location".to_string(), Some("Lisbon".to_string())),
+            ]),
+            ..Default::default()
+        };
+        profile.apply(&config, &update).unwrap();
+        assert_eq!(profile.values["github"], "https://github.com/jdoe");
+        assert_eq!(profile.values["website"], "https://jdoe.dev/");
+
+        let bad_url = ProfileUpdate {
+            fields: HashMap::from([(
+                "website".to_string(),
+                Some("javascript:alert(1)".to_string()),
+            )]),
+            ..Default::default()
+        };
+        assert!(profile.apply(&config, &bad_url).is_err());
+
+        let unknown = ProfileUpdate {
+            fields: HashMap::from([("shoe_size".to_string(), Some("42".to_string()))]),
+            ..Default::default()
+        };
+        assert!(profile.apply(&config, &unknown).is_err());
+
+        let clear = ProfileUpdate {
+            fields: HashMap::from([("location".to_string(), None)]),
+            ..Default::default()
+        };
+        profile.apply(&config, &clear).unwrap();
+        assert!(!profile.values.contains_key("location"));
+    }
+
+    #[test]
+    fn test_visibility_per_viewer() {
+        let config = ProfileFieldsConfig::default();
+        let row = row(json!({
+            "profile": {"location": "Lisbon", "pronouns": "they/them", "github": "https://github.com/jdoe"},
+            "profile_visibility": {"pronouns": "private"},
+        }));
+        let profile = StoredProfile::from_row(&row);
+
+        let public = profile.view(&row, &config, ProfileViewer::Anonymous);
+        assert_eq!(
+            public.values.get("bio").map(String::as_str),
+            Some("Writes about </script> things")
+        );
+        assert!(!public.values.contains_key("location"));
+        assert!(!public.values.contains_key("pronouns"));
+        assert_eq!(public.social_links.len(), 1);
+        assert!(public.avatar.is_none());
+
+        let member = profile.view(&row, &config, ProfileViewer::Member);
+        assert!(member.values.contains_key("location"));
+        assert!(!member.values.contains_key("pronouns"));
+
+        let owner = profile.view(&row, &config, ProfileViewer::Owner);
+        assert!(owner.values.contains_key("pronouns"));
+        assert_eq!(owner.avatar, Some(AvatarSource::Gravatar));
+
+        let mut profile = profile;
+        let upload = ProfileUpdate {
+            avatar: Some(AvatarSource::Upload {
+                path: "../../etc/passwd".to_string(),
+            }),
+            ..Default::default()
+        };
+        assert!(profile.apply(&config, &upload).is_err());
+    }
+
+    #[test]
+    fn test_structured_data() {
+        let config = ProfileFieldsConfig::default();
+        let row = row(json!({"profile": {"github": "https://github.com/jdoe"}}));
+        let view = StoredProfile::from_row(&row).view(&row, &config, ProfileViewer::Anonymous);
+
+        let data = view.structured_data("https://example.com/");
+        assert_eq!(data["@type"], "ProfilePage");
+        assert_eq!(data["mainEntity"]["url"], "https://example.com/author/jdoe");
+        assert_eq!(data["mainEntity"]["sameAs"][0], "https://github.com/jdoe");
+
+        let html = inject_json_ld("<html><head></head><body></body></html>", &data);
+        assert!(html.contains("<script type=\"application/ld+json\">"));
+        assert!(!html.contains("</script> things"));
+        assert_eq!(
+            data["mainEntity"]["image"],
+            "https://example.com/avatar/00000000-0000-0000-0000-000000000000?s=96"
+        );
+    }
+}
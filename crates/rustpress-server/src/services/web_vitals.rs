@@ -0,0 +1,1333 @@
+//! Core Web Vitals
+//!
+//! Field data on how fast pages are for real visitors, and performance
+//! budgets that flag templates getting slower:
+//!
+//! - while enabled, rendered pages carry a small script that observes LCP,
+//!   CLS and INP and sends them to [`BEACON_PATH`] with `sendBeacon` once
+//!   the page is hidden; a share of page views can be sampled
+//! - samples are rolled up per day, path, device and metric into counts
+//!   per rating and a logarithmic histogram, so the percentiles of any
+//!   period and grouping are merged from the rollups without keeping raw
+//!   samples. Percentiles are accurate to about 5%
+//! - the device is taken from the User-Agent and the template from the
+//!   path, never from the beacon
+//! - beacons only count for pages of this site, and each instance records
+//!   at most 10,000 distinct paths a day, so made-up paths cannot fill
+//!   the rollups
+//! - budgets cap the p75 of a metric per template, optionally per device,
+//!   and how far it may regress against the window before. The hourly
+//!   `evaluate_web_vitals_budgets` job opens an alert for each budget
+//!   exceeded and resolves it once p75 is back within budget, publishing
+//!   [`BUDGET_EXCEEDED_EVENT`] and [`BUDGET_RECOVERED_EVENT`]
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use rustpress_core::error::{Error, Result};
+use rustpress_events::{DomainEvent, EventBus};
+use rustpress_jobs::{JobHandler, JobPayload};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::sync::Arc;
+use tokio::sync::{Mutex, RwLock};
+use uuid::Uuid;
+
+use super::json_setting::JsonSetting;
+
+/// Settings key holding the Core Web Vitals configuration
+pub const WEB_VITALS_SETTINGS_KEY: &str = "web_vitals_config";
+
+/// Stored Core Web Vitals settings
+const WEB_VITALS_SETTING: JsonSetting<WebVitalsConfig> = JsonSetting::new(
+    WEB_VITALS_SETTINGS_KEY,
+    "performance",
+    "Core Web Vitals settings",
+);
+
+/// Where browsers send their measurements
+pub const BEACON_PATH: &str = "/rum/vitals";
+
+/// Largest beacon accepted, in bytes
+pub const MAX_BEACON_BYTES: usize = 4 * 1024;
+
+/// Published when a template goes over one of its budgets
+pub const BUDGET_EXCEEDED_EVENT: &str = "web_vitals.budget_exceeded";
+
+/// Published when a template is back within a budget it went over
+pub const BUDGET_RECOVERED_EVENT: &str = "web_vitals.budget_recovered";
+
+/// Templates pages are grouped into, see [`template_for_path`]
+pub const TEMPLATES: &[&str] = &[
+    "home", "post", "page", "event", "archive", "search", "other",
+];
+
+/// Buckets of a histogram; the last one takes every larger value
+pub const HISTOGRAM_BUCKETS: usize = 128;
+
+/// Ratio between the bounds of a bucket
+const BUCKET_GROWTH: f64 = 1.1;
+
+/// Longest path recorded
+const MAX_PATH_LENGTH: usize = 512;
+
+/// Distinct paths recorded a day by each instance, so beacons for made-up
+/// paths cannot grow the rollups without bound
+const MAX_PATHS_PER_DAY: usize = 10_000;
+
+/// Days a report may cover
+const MAX_REPORT_DAYS: i64 = 366;
+
+/// Pages listed in a report
+const REPORT_PAGES: i64 = 20;
+
+/// Longest budget window
+const MAX_WINDOW_DAYS: u32 = 90;
+
+/// Budgets that can be configured
+const MAX_BUDGETS: usize = 100;
+
+/// Alerts listed at once
+const MAX_ALERTS: i64 = 200;
+
+/// A Core Web Vital
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum VitalMetric {
+    /// Largest Contentful Paint, in milliseconds
+    Lcp,
+    /// Cumulative Layout Shift, unitless
+    Cls,
+    /// Interaction to Next Paint, in milliseconds
+    Inp,
+}
+
+impl VitalMetric {
+    pub const ALL: [VitalMetric; 3] = [Self::Lcp, Self::Cls, Self::Inp];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Lcp => "LCP",
+            Self::Cls => "CLS",
+            Self::Inp => "INP",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "LCP" => Some(Self::Lcp),
+            "CLS" => Some(Self::Cls),
+            "INP" => Some(Self::Inp),
+            _ => None,
+        }
+    }
+
+    /// Highest good value and highest value that only needs improvement,
+    /// as Google rates them
+    pub fn thresholds(&self) -> (f64, f64) {
+        match self {
+            Self::Lcp => (2_500.0, 4_000.0),
+            Self::Cls => (0.1, 0.25),
+            Self::Inp => (200.0, 500.0),
+        }
+    }
+
+    pub fn rating(&self, value: f64) -> Rating {
+        let (good, needs_improvement) = self.thresholds();
+        if value <= good {
+            Rating::Good
+        } else if value <= needs_improvement {
+            Rating::NeedsImprovement
+        } else {
+            Rating::Poor
+        }
+    }
+
+    /// Largest plausible value; anything above is a broken measurement
+    fn max_value(&self) -> f64 {
+        match self {
+            Self::Lcp | Self::Inp => 600_000.0,
+            Self::Cls => 100.0,
+        }
+    }
+
+    /// Factor bringing values to the resolution of the histogram: layout
+    /// shifts are counted in thousandths
+    fn scale(&self) -> f64 {
+        match self {
+            Self::Lcp | Self::Inp => 1.0,
+            Self::Cls => 1_000.0,
+        }
+    }
+
+    /// Round a value to what the metric is reported in
+    fn round(&self, value: f64) -> f64 {
+        match self {
+            Self::Lcp | Self::Inp => value.round(),
+            Self::Cls => (value * 1_000.0).round() / 1_000.0,
+        }
+    }
+}
+
+/// How a value compares to Google's thresholds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rating {
+    Good,
+    NeedsImprovement,
+    Poor,
+}
+
+/// Kind of device a page was viewed on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Device {
+    Mobile,
+    Tablet,
+    Desktop,
+}
+
+impl Device {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Mobile => "mobile",
+            Self::Tablet => "tablet",
+            Self::Desktop => "desktop",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "mobile" => Some(Self::Mobile),
+            "tablet" => Some(Self::Tablet),
+            "desktop" => Some(Self::Desktop),
+            _ => None,
+        }
+    }
+
+    /// Device a User-Agent describes
+    pub fn from_user_agent(user_agent: &str) -> Self {
+        let ua = user_agent.to_ascii_lowercase();
+        if ua.contains("ipad") || ua.contains("tablet") || ua.contains("kindle") {
+            Self::Tablet
+        } else if ua.contains("mobi") || ua.contains("iphone") || ua.contains("ipod") {
+            Self::Mobile
+        } else if ua.contains("android") {
+            // Android tablets leave "Mobile" out of their User-Agent
+            Self::Tablet
+        } else {
+            Self::Desktop
+        }
+    }
+}
+
+/// Template a public path is rendered with, from the public routes
+pub fn template_for_path(path: &str) -> &'static str {
+    let mut segments = path.trim_matches('/').split('/');
+    let first = segments.next().unwrap_or_default();
+    match first {
+        "" => "home",
+        "post" => "post",
+        "page" => "page",
+        "event" => "event",
+        "search" => "search",
+        "blog" | "events" | "category" | "tag" | "author" => "archive",
+        _ if first.bytes().all(|b| b.is_ascii_digit()) && segments.next().is_some() => "archive",
+        _ => "other",
+    }
+}
+
+/// Path of a page URL reported by a beacon. Full URLs must be on the site
+/// at `site_url`
+pub fn beacon_path(url: &str, site_url: &str) -> Option<String> {
+    if !url.contains("://") {
+        return normalize_path(url);
+    }
+    let page = reqwest::Url::parse(url).ok()?;
+    let site = reqwest::Url::parse(site_url).ok()?;
+    let same_site = matches!(page.scheme(), "http" | "https")
+        && page.host_str().is_some()
+        && page.host_str() == site.host_str()
+        && page.port_or_known_default() == site.port_or_known_default();
+    if !same_site {
+        return None;
+    }
+    normalize_path(page.path())
+}
+
+/// Page path without query, fragment or trailing slash
+pub fn normalize_path(path: &str) -> Option<String> {
+    let path = path.split(['?', '#']).next().unwrap_or_default();
+    if !path.starts_with('/')
+        || path.starts_with("//")
+        || path.len() > MAX_PATH_LENGTH
+        || path.chars().any(|c| c.is_control() || c.is_whitespace())
+    {
+        return None;
+    }
+    let trimmed = path.trim_end_matches('/');
+    Some(if trimmed.is_empty() { "/" } else { trimmed }.to_string())
+}
+
+/// Bucket of the histogram a value falls into. Bucket 0 holds values
+/// below one (millisecond or thousandth), bucket `i` values from
+/// `1.1^(i-1)` up to `1.1^i`
+pub fn bucket_of(metric: VitalMetric, value: f64) -> usize {
+    let scaled = value * metric.scale();
+    if scaled < 1.0 {
+        return 0;
+    }
+    let bucket = (scaled.ln() / BUCKET_GROWTH.ln()).floor() as usize + 1;
+    bucket.min(HISTOGRAM_BUCKETS - 1)
+}
+
+/// Value standing for a bucket: the geometric middle of its bounds
+fn bucket_value(metric: VitalMetric, bucket: usize) -> f64 {
+    if bucket == 0 {
+        return 0.0;
+    }
+    BUCKET_GROWTH.powf(bucket as f64 - 0.5) / metric.scale()
+}
+
+/// Value below which `quantile` of the samples of a histogram fall
+pub fn percentile(metric: VitalMetric, histogram: &[i64], quantile: f64) -> Option<f64> {
+    let total: i64 = histogram.iter().sum();
+    if total <= 0 {
+        return None;
+    }
+    let rank = ((quantile * total as f64).ceil() as i64).max(1);
+    let mut seen = 0;
+    for (bucket, count) in histogram.iter().enumerate() {
+        seen += count;
+        if seen >= rank {
+            return Some(metric.round(bucket_value(metric, bucket)));
+        }
+    }
+    None
+}
+
+/// A performance budget
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VitalsBudget {
+    /// Template the budget applies to; every template when absent
+    #[serde(default)]
+    pub template: Option<String>,
+    pub metric: VitalMetric,
+    /// Device the budget applies to; all devices together when absent
+    #[serde(default)]
+    pub device: Option<Device>,
+    /// Highest p75 allowed, in milliseconds for LCP and INP
+    pub p75: f64,
+    /// Largest rise of p75 over the window before, in percent
+    #[serde(default)]
+    pub max_regression_percent: Option<f64>,
+}
+
+/// Collection and budget settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebVitalsConfig {
+    /// Add the measuring script to pages and accept beacons
+    pub enabled: bool,
+    /// Share of page views measured, from 0 to 1
+    pub sample_rate: f64,
+    /// Days each budget check covers; the days before are the baseline
+    pub window_days: u32,
+    /// Samples a template needs in a window for its budgets to be checked
+    pub min_samples: i64,
+    pub budgets: Vec<VitalsBudget>,
+}
+
+impl Default for WebVitalsConfig {
+    fn default() -> Self {
+        // Every template is held to Google's "good" thresholds
+        let budgets = VitalMetric::ALL
+            .iter()
+            .map(|metric| VitalsBudget {
+                template: None,
+                metric: *metric,
+                device: None,
+                p75: metric.thresholds().0,
+                max_regression_percent: None,
+            })
+            .collect();
+        Self {
+            enabled: false,
+            sample_rate: 1.0,
+            window_days: 7,
+            min_samples: 100,
+            budgets,
+        }
+    }
+}
+
+impl WebVitalsConfig {
+    /// Load the configuration from settings, falling back to defaults
+    pub async fn load(pool: &PgPool) -> Result<Self> {
+        WEB_VITALS_SETTING.load_or_default(pool).await
+    }
+
+    /// Persist the configuration to settings
+    pub async fn save(&self, pool: &PgPool) -> Result<()> {
+        WEB_VITALS_SETTING.save(pool, self).await
+    }
+
+    /// Validate the sampling, window and budgets
+    pub fn validate(&self) -> Result<()> {
+        if !(self.sample_rate > 0.0 && self.sample_rate <= 1.0) {
+            return Err(Error::invalid_input(
+                "sample_rate",
+                "The sample rate is a share above 0 and up to 1",
+            ));
+        }
+        if !(1..=MAX_WINDOW_DAYS).contains(&self.window_days) {
+            return Err(Error::invalid_input(
+                "window_days",
+                format!("Budget windows are 1 to {} days", MAX_WINDOW_DAYS),
+            ));
+        }
+        if self.min_samples < 1 {
+            return Err(Error::invalid_input(
+                "min_samples",
+                "Budgets need at least one sample",
+            ));
+        }
+        if self.budgets.len() > MAX_BUDGETS {
+            return Err(Error::invalid_input(
+                "budgets",
+                format!("At most {} budgets can be set", MAX_BUDGETS),
+            ));
+        }
+
+        let mut seen = BTreeSet::new();
+        for budget in &self.budgets {
+            if let Some(template) = &budget.template {
+                if !TEMPLATES.contains(&template.as_str()) {
+                    return Err(Error::invalid_input(
+                        "budgets",
+                        format!(
+                            "Unknown template '{}'; templates are {}",
+                            template,
+                            TEMPLATES.join(", ")
+                        ),
+                    ));
+                }
+            }
+            if !(budget.p75 > 0.0 && budget.p75 <= budget.metric.max_value()) {
+                return Err(Error::invalid_input(
+                    "budgets",
+                    format!("Invalid {} budget", budget.metric.as_str()),
+                ));
+            }
+            if let Some(percent) = budget.max_regression_percent {
+                if !(percent > 0.0 && percent.is_finite()) {
+                    return Err(Error::invalid_input(
+                        "budgets",
+                        "Allowed regressions are positive percentages",
+                    ));
+                }
+            }
+            if !seen.insert((budget.template.clone(), budget.metric, budget.device)) {
+                return Err(Error::invalid_input(
+                    "budgets",
+                    format!(
+                        "The {} budget of {} is set twice",
+                        budget.metric.as_str(),
+                        budget.template.as_deref().unwrap_or("every template")
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Budget of each template, metric and device; a template's own budget
+    /// replaces the one set for every template
+    fn effective_budgets(&self) -> BTreeMap<BudgetKey, &VitalsBudget> {
+        let mut budgets = BTreeMap::new();
+        for budget in self.budgets.iter().filter(|b| b.template.is_none()) {
+            for template in TEMPLATES {
+                budgets.insert((template.to_string(), budget.metric, budget.device), budget);
+            }
+        }
+        for budget in &self.budgets {
+            if let Some(template) = &budget.template {
+                budgets.insert((template.clone(), budget.metric, budget.device), budget);
+            }
+        }
+        budgets
+    }
+
+    /// Script measuring the page and sending a beacon once it is hidden
+    pub fn beacon_script(&self) -> String {
+        BEACON_SCRIPT
+            .replace("__RATE__", &self.sample_rate.to_string())
+            .replace("__PATH__", BEACON_PATH)
+    }
+}
+
+/// Template, metric and device a budget applies to
+type BudgetKey = (String, VitalMetric, Option<Device>);
+
+/// Observes LCP, CLS (largest session window) and INP (slowest
+/// interaction) and sends them once when the page is first hidden
+const BEACON_SCRIPT: &str = r#"<script>(function(){var P=window.PerformanceObserver;if(!P||!navigator.sendBeacon||Math.random()>=__RATE__)return;var t=P.supportedEntryTypes||[],v={},w=0,f=0,l=0,s=0;function o(y,c,x){if(t.indexOf(y)<0)return;try{var b={type:y,buffered:true};for(var k in x)b[k]=x[k];new P(function(e){e.getEntries().forEach(c)}).observe(b)}catch(e){}}o('largest-contentful-paint',function(e){v.LCP=e.startTime});if(t.indexOf('layout-shift')>=0)v.CLS=0;o('layout-shift',function(e){if(e.hadRecentInput)return;if(w&&e.startTime-l<1000&&e.startTime-f<5000){w+=e.value}else{w=e.value;f=e.startTime}l=e.startTime;if(w>v.CLS)v.CLS=w});o('event',function(e){if(e.interactionId&&!(e.duration<=v.INP))v.INP=e.duration},{durationThreshold:40});addEventListener('visibilitychange',function(){if(s||document.visibilityState!=='hidden')return;var m=[];for(var k in v)m.push({name:k,value:v[k]});if(m.length){s=1;navigator.sendBeacon('__PATH__',JSON.stringify({url:location.pathname,metrics:m}))}})})();</script>"#;
+
+/// Measurements of one page view, as sent by the beacon script
+#[derive(Debug, Clone, Deserialize)]
+pub struct VitalsBeacon {
+    /// Path or URL of the page
+    pub url: String,
+    pub metrics: Vec<VitalSample>,
+}
+
+/// One measurement; metrics other than LCP, CLS and INP are ignored
+#[derive(Debug, Clone, Deserialize)]
+pub struct VitalSample {
+    pub name: String,
+    pub value: f64,
+}
+
+/// Report query
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct WebVitalsQuery {
+    /// First day, 28 days ago by default
+    pub from: Option<NaiveDate>,
+    /// Last day, today by default
+    pub to: Option<NaiveDate>,
+    pub device: Option<Device>,
+    pub template: Option<String>,
+    pub path: Option<String>,
+}
+
+/// A metric over a group of page views
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricSummary {
+    pub metric: VitalMetric,
+    pub samples: i64,
+    pub p75: Option<f64>,
+    /// Rating of the p75
+    pub rating: Option<Rating>,
+    pub good: i64,
+    pub needs_improvement: i64,
+    pub poor: i64,
+}
+
+/// Metrics of a device or template
+#[derive(Debug, Clone, Serialize)]
+pub struct VitalsGroup {
+    pub name: String,
+    pub metrics: Vec<MetricSummary>,
+}
+
+/// Metrics of a page
+#[derive(Debug, Clone, Serialize)]
+pub struct PageVitals {
+    pub path: String,
+    pub template: &'static str,
+    pub metrics: Vec<MetricSummary>,
+}
+
+/// Metrics of a day
+#[derive(Debug, Clone, Serialize)]
+pub struct DailyVitals {
+    pub day: NaiveDate,
+    pub metrics: Vec<MetricSummary>,
+}
+
+/// Field data of a period
+#[derive(Debug, Clone, Serialize)]
+pub struct WebVitalsReport {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub metrics: Vec<MetricSummary>,
+    pub devices: Vec<VitalsGroup>,
+    pub templates: Vec<VitalsGroup>,
+    /// Pages with the most samples
+    pub pages: Vec<PageVitals>,
+    pub daily: Vec<DailyVitals>,
+    /// Budgets currently exceeded
+    pub alerts: Vec<VitalsAlert>,
+}
+
+/// A budget a template went over
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct VitalsAlert {
+    pub id: Uuid,
+    pub template: String,
+    /// Device of the budget; all devices when absent
+    pub device: Option<String>,
+    pub metric: String,
+    /// Highest p75 the budget allows
+    pub budget: f64,
+    pub p75: f64,
+    /// p75 of the window before
+    pub baseline_p75: Option<f64>,
+    pub samples: i64,
+    /// `over_budget` or `regressed`
+    pub reason: String,
+    pub opened_at: DateTime<Utc>,
+    pub checked_at: DateTime<Utc>,
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
+/// Alert list query
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct VitalsAlertQuery {
+    /// Only open (`true`) or resolved (`false`) alerts
+    pub open: Option<bool>,
+    pub limit: Option<i64>,
+}
+
+/// Outcome of a budget check
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct BudgetCheck {
+    /// Budgets with enough samples to be checked
+    pub checked: u32,
+    pub opened: u32,
+    pub resolved: u32,
+}
+
+/// Why a budget is exceeded
+fn breach_reason(
+    budget: &VitalsBudget,
+    p75: f64,
+    baseline_p75: Option<f64>,
+) -> Option<&'static str> {
+    if p75 > budget.p75 {
+        return Some("over_budget");
+    }
+    match (budget.max_regression_percent, baseline_p75) {
+        (Some(percent), Some(baseline)) if baseline > 0.0 => {
+            (p75 > baseline * (1.0 + percent / 100.0)).then_some("regressed")
+        }
+        _ => None,
+    }
+}
+
+/// Summed rollups of one group and metric
+#[derive(Debug, FromRow)]
+struct RollupRow {
+    key: String,
+    metric: String,
+    samples: i64,
+    good: i64,
+    needs_improvement: i64,
+    poor: i64,
+    histogram: Vec<i64>,
+}
+
+impl RollupRow {
+    fn summary(&self) -> Option<MetricSummary> {
+        let metric = VitalMetric::from_name(&self.metric)?;
+        let p75 = percentile(metric, &self.histogram, 0.75);
+        Some(MetricSummary {
+            metric,
+            samples: self.samples,
+            p75,
+            rating: p75.map(|p75| metric.rating(p75)),
+            good: self.good,
+            needs_improvement: self.needs_improvement,
+            poor: self.poor,
+        })
+    }
+}
+
+/// Summaries per group, in the order of the groups
+fn by_key(rows: Vec<RollupRow>) -> Vec<(String, Vec<MetricSummary>)> {
+    let mut groups: Vec<(String, Vec<MetricSummary>)> = Vec::new();
+    for row in rows {
+        let Some(summary) = row.summary() else {
+            continue;
+        };
+        match groups.last_mut() {
+            Some((key, metrics)) if *key == row.key => metrics.push(summary),
+            _ => groups.push((row.key, vec![summary])),
+        }
+    }
+    for (_, metrics) in &mut groups {
+        metrics.sort_by_key(|m| m.metric);
+    }
+    groups
+}
+
+/// Rows a rollup query covers
+struct RollupFilter<'a> {
+    from: NaiveDate,
+    to: NaiveDate,
+    device: Option<Device>,
+    template: Option<&'a str>,
+    path: Option<&'a str>,
+    paths: Option<&'a [String]>,
+}
+
+const ROLLUP_FILTER: &str = "day BETWEEN $1 AND $2 AND ($3::text IS NULL OR device = $3) \
+     AND ($4::text IS NULL OR template = $4) AND ($5::text IS NULL OR path = $5) \
+     AND ($6::text[] IS NULL OR path = ANY($6))";
+
+pub struct WebVitalsService {
+    pool: PgPool,
+    events: Option<Arc<EventBus>>,
+    config: RwLock<Option<Arc<WebVitalsConfig>>>,
+    /// Paths in the rollups of the day, see [`MAX_PATHS_PER_DAY`]
+    paths_today: Mutex<Option<(NaiveDate, HashSet<String>)>>,
+}
+
+impl WebVitalsService {
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            events: None,
+            config: RwLock::new(None),
+            paths_today: Mutex::new(None),
+        }
+    }
+
+    /// Publish budget alerts on the event bus
+    pub fn with_events(mut self, events: Arc<EventBus>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Current configuration, loaded from settings on first use
+    pub async fn config(&self) -> Arc<WebVitalsConfig> {
+        if let Some(config) = self.config.read().await.clone() {
+            return config;
+        }
+
+        match WebVitalsConfig::load(&self.pool).await {
+            Ok(config) => {
+                let config = Arc::new(config);
+                *self.config.write().await = Some(config.clone());
+                config
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "Failed to load Core Web Vitals settings, collection is off: {}",
+                    e
+                );
+                Arc::new(WebVitalsConfig::default())
+            }
+        }
+    }
+
+    /// Validate and save a new configuration
+    pub async fn update_config(&self, config: WebVitalsConfig) -> Result<WebVitalsConfig> {
+        config.validate()?;
+        config.save(&self.pool).await?;
+        *self.config.write().await = Some(Arc::new(config.clone()));
+        Ok(config)
+    }
+
+    /// Drop the cached configuration; the next use reloads it
+    pub async fn invalidate(&self) {
+        *self.config.write().await = None;
+    }
+
+    /// Measuring script for rendered pages, while collection is on
+    pub async fn beacon_script(&self) -> Option<String> {
+        let config = self.config().await;
+        config.enabled.then(|| config.beacon_script())
+    }
+
+    /// Add the measurements of a page view on the site at `site_url` to
+    /// today's rollups, returning how many were recorded
+    pub async fn record(
+        &self,
+        beacon: &VitalsBeacon,
+        user_agent: &str,
+        site_url: &str,
+    ) -> Result<u32> {
+        if !self.config().await.enabled {
+            return Ok(0);
+        }
+        let path = beacon_path(&beacon.url, site_url)
+            .ok_or_else(|| Error::invalid_input("url", "Not a page of this site"))?;
+        let template = template_for_path(&path);
+        let device = Device::from_user_agent(user_agent);
+        let day = Utc::now().date_naive();
+
+        // Each metric once, the first value winning
+        let mut samples = BTreeMap::new();
+        for sample in &beacon.metrics {
+            let Some(metric) = VitalMetric::from_name(&sample.name) else {
+                continue;
+            };
+            if !(sample.value >= 0.0 && sample.value <= metric.max_value()) {
+                return Err(Error::invalid_input(
+                    "metrics",
+                    format!("Invalid {} value", metric.as_str()),
+                ));
+            }
+            samples.entry(metric).or_insert(sample.value);
+        }
+        if samples.is_empty() || !self.admit_path(day, &path).await? {
+            return Ok(0);
+        }
+
+        for (metric, value) in &samples {
+            let bucket = bucket_of(*metric, *value);
+            let mut histogram = vec![0i64; HISTOGRAM_BUCKETS];
+            histogram[bucket] = 1;
+            let rating = metric.rating(*value);
+
+            sqlx::query(
+                "INSERT INTO web_vitals_daily \
+                 (day, path, template, device, metric, samples, good, needs_improvement, poor, histogram) \
+                 VALUES ($1, $2, $3, $4, $5, 1, $6, $7, $8, $9) \
+                 ON CONFLICT (day, path, device, metric) DO UPDATE SET \
+                 samples = web_vitals_daily.samples + 1, \
+                 good = web_vitals_daily.good + EXCLUDED.good, \
+                 needs_improvement = web_vitals_daily.needs_improvement + EXCLUDED.needs_improvement, \
+                 poor = web_vitals_daily.poor + EXCLUDED.poor, \
+                 histogram[$10] = COALESCE(web_vitals_daily.histogram[$10], 0) + 1",
+            )
+            .bind(day)
+            .bind(&path)
+            .bind(template)
+            .bind(device.as_str())
+            .bind(metric.as_str())
+            .bind(i64::from(rating == Rating::Good))
+            .bind(i64::from(rating == Rating::NeedsImprovement))
+            .bind(i64::from(rating == Rating::Poor))
+            .bind(&histogram)
+            // Arrays are 1-based in SQL
+            .bind(bucket as i32 + 1)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to record Core Web Vitals", e))?;
+        }
+        Ok(samples.len() as u32)
+    }
+
+    /// Whether `path` may be recorded on `day`: paths already in the day's
+    /// rollups always are, new ones until the day's cap is reached
+    async fn admit_path(&self, day: NaiveDate, path: &str) -> Result<bool> {
+        let mut paths = self.paths_today.lock().await;
+        if paths.as_ref().map_or(true, |(loaded, _)| *loaded != day) {
+            let recorded: Vec<(String,)> =
+                sqlx::query_as("SELECT DISTINCT path FROM web_vitals_daily WHERE day = $1")
+                    .bind(day)
+                    .fetch_all(&self.pool)
+                    .await
+                    .map_err(|e| {
+                        Error::database_with_source("Failed to load Core Web Vitals paths", e)
+                    })?;
+            *paths = Some((day, recorded.into_iter().map(|(path,)| path).collect()));
+        }
+        let Some((_, known)) = paths.as_mut() else {
+            return Ok(false);
+        };
+        if known.contains(path) {
+            return Ok(true);
+        }
+        if known.len() >= MAX_PATHS_PER_DAY {
+            tracing::debug!(path, "Core Web Vitals path cap reached, sample dropped");
+            return Ok(false);
+        }
+        known.insert(path.to_string());
+        Ok(true)
+    }
+
+    /// Rollups summed per group; `key` is an SQL expression naming the group
+    async fn rollups(&self, key: &str, filter: &RollupFilter<'_>) -> Result<Vec<RollupRow>> {
+        sqlx::query_as(&format!(
+            "WITH scoped AS ( \
+                 SELECT {key} AS key, metric, samples, good, needs_improvement, poor, histogram \
+                 FROM web_vitals_daily WHERE {filter} \
+             ), totals AS ( \
+                 SELECT key, metric, SUM(samples)::bigint AS samples, SUM(good)::bigint AS good, \
+                 SUM(needs_improvement)::bigint AS needs_improvement, SUM(poor)::bigint AS poor \
+                 FROM scoped GROUP BY key, metric \
+             ), buckets AS ( \
+                 SELECT key, metric, h.i, SUM(COALESCE(h.c, 0))::bigint AS c \
+                 FROM scoped, unnest(scoped.histogram) WITH ORDINALITY AS h(c, i) \
+                 GROUP BY key, metric, h.i \
+             ), histograms AS ( \
+                 SELECT key, metric, array_agg(c ORDER BY i) AS histogram \
+                 FROM buckets GROUP BY key, metric \
+             ) \
+             SELECT t.key, t.metric, t.samples, t.good, t.needs_improvement, t.poor, \
+             COALESCE(h.histogram, '{{}}') AS histogram \
+             FROM totals t LEFT JOIN histograms h USING (key, metric) \
+             ORDER BY t.key, t.metric",
+            key = key,
+            filter = ROLLUP_FILTER
+        ))
+        .bind(filter.from)
+        .bind(filter.to)
+        .bind(filter.device.map(|d| d.as_str()))
+        .bind(filter.template)
+        .bind(filter.path)
+        .bind(filter.paths)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load Core Web Vitals", e))
+    }
+
+    /// Percentiles and ratings per metric, device, template, page and day
+    pub async fn report(&self, query: &WebVitalsQuery) -> Result<WebVitalsReport> {
+        let to = query.to.unwrap_or_else(|| Utc::now().date_naive());
+        let from = query.from.unwrap_or(to - Duration::days(27));
+        if from > to || (to - from).num_days() >= MAX_REPORT_DAYS {
+            return Err(Error::invalid_input(
+                "from",
+                format!(
+                    "Reports cover 1 to {} days, starting before they end",
+                    MAX_REPORT_DAYS
+                ),
+            ));
+        }
+        if let Some(template) = &query.template {
+            if !TEMPLATES.contains(&template.as_str()) {
+                return Err(Error::invalid_input("template", "Unknown template"));
+            }
+        }
+        let path = match &query.path {
+            Some(path) => Some(
+                normalize_path(path).ok_or_else(|| Error::invalid_input("path", "Invalid path"))?,
+            ),
+            None => None,
+        };
+
+        let mut filter = RollupFilter {
+            from,
+            to,
+            device: query.device,
+            template: query.template.as_deref(),
+            path: path.as_deref(),
+            paths: None,
+        };
+        let metrics = by_key(self.rollups("''", &filter).await?)
+            .into_iter()
+            .next()
+            .map(|(_, metrics)| metrics)
+            .unwrap_or_default();
+        let group = |(name, metrics): (String, Vec<MetricSummary>)| VitalsGroup { name, metrics };
+        let devices = by_key(self.rollups("device", &filter).await?)
+            .into_iter()
+            .map(group)
+            .collect();
+        let templates = by_key(self.rollups("template", &filter).await?)
+            .into_iter()
+            .map(group)
+            .collect();
+        let daily = by_key(self.rollups("day::text", &filter).await?)
+            .into_iter()
+            .filter_map(|(day, metrics)| {
+                Some(DailyVitals {
+                    day: day.parse().ok()?,
+                    metrics,
+                })
+            })
+            .collect();
+
+        let top: Vec<String> = sqlx::query_scalar(&format!(
+            "SELECT path FROM web_vitals_daily WHERE {} \
+             GROUP BY path ORDER BY SUM(samples) DESC, path LIMIT {}",
+            ROLLUP_FILTER, REPORT_PAGES
+        ))
+        .bind(filter.from)
+        .bind(filter.to)
+        .bind(filter.device.map(|d| d.as_str()))
+        .bind(filter.template)
+        .bind(filter.path)
+        .bind(filter.paths)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load Core Web Vitals", e))?;
+        filter.paths = Some(&top);
+        let mut by_path: HashMap<String, Vec<MetricSummary>> =
+            by_key(self.rollups("path", &filter).await?)
+                .into_iter()
+                .collect();
+        let pages = top
+            .iter()
+            .filter_map(|path| {
+                Some(PageVitals {
+                    template: template_for_path(path),
+                    metrics: by_path.remove(path)?,
+                    path: path.clone(),
+                })
+            })
+            .collect();
+
+        let alerts = self
+            .alerts(&VitalsAlertQuery {
+                open: Some(true),
+                limit: None,
+            })
+            .await?;
+
+        Ok(WebVitalsReport {
+            from,
+            to,
+            metrics,
+            devices,
+            templates,
+            pages,
+            daily,
+            alerts,
+        })
+    }
+
+    /// Budget alerts, newest first
+    pub async fn alerts(&self, query: &VitalsAlertQuery) -> Result<Vec<VitalsAlert>> {
+        sqlx::query_as(
+            "SELECT * FROM web_vitals_alerts \
+             WHERE ($1::bool IS NULL OR (resolved_at IS NULL) = $1) \
+             ORDER BY opened_at DESC LIMIT $2",
+        )
+        .bind(query.open)
+        .bind(query.limit.unwrap_or(50).clamp(1, MAX_ALERTS))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to list Core Web Vitals alerts", e))
+    }
+
+    /// p75 per template and metric over a window, for templates with enough
+    /// samples
+    async fn template_p75(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+        device: Option<Device>,
+        min_samples: i64,
+    ) -> Result<HashMap<(String, VitalMetric), (f64, i64)>> {
+        let filter = RollupFilter {
+            from,
+            to,
+            device,
+            template: None,
+            path: None,
+            paths: None,
+        };
+        let mut p75 = HashMap::new();
+        for (template, metrics) in by_key(self.rollups("template", &filter).await?) {
+            for summary in metrics {
+                if let Some(value) = summary.p75.filter(|_| summary.samples >= min_samples) {
+                    p75.insert((template.clone(), summary.metric), (value, summary.samples));
+                }
+            }
+        }
+        Ok(p75)
+    }
+
+    /// Check every budget against the current window, opening alerts for
+    /// budgets exceeded and resolving those back within budget
+    pub async fn check_budgets(&self) -> Result<BudgetCheck> {
+        let config = self.config().await;
+        let mut check = BudgetCheck::default();
+        if !config.enabled {
+            return Ok(check);
+        }
+        let db = |e| Error::database_with_source("Failed to check performance budgets", e);
+
+        let window = i64::from(config.window_days);
+        let to = Utc::now().date_naive();
+        let from = to - Duration::days(window - 1);
+        let (baseline_from, baseline_to) =
+            (from - Duration::days(window), from - Duration::days(1));
+
+        let budgets = config.effective_budgets();
+        let mut current = HashMap::new();
+        let mut baseline = HashMap::new();
+        for device in budgets.keys().map(|(_, _, device)| *device) {
+            if current.contains_key(&device) {
+                continue;
+            }
+            current.insert(
+                device,
+                self.template_p75(from, to, device, config.min_samples)
+                    .await?,
+            );
+            baseline.insert(
+                device,
+                self.template_p75(baseline_from, baseline_to, device, config.min_samples)
+                    .await?,
+            );
+        }
+
+        let open: Vec<VitalsAlert> =
+            sqlx::query_as("SELECT * FROM web_vitals_alerts WHERE resolved_at IS NULL")
+                .fetch_all(&self.pool)
+                .await
+                .map_err(db)?;
+        let mut open: HashMap<BudgetKey, VitalsAlert> = open
+            .into_iter()
+            .filter_map(|alert| {
+                let metric = VitalMetric::from_name(&alert.metric)?;
+                let device = match &alert.device {
+                    Some(device) => Some(Device::from_name(device)?),
+                    None => None,
+                };
+                Some(((alert.template.clone(), metric, device), alert))
+            })
+            .collect();
+
+        for (key, budget) in &budgets {
+            let (template, metric, device) = key;
+            let lookup = (template.clone(), *metric);
+            // Too few samples to judge: leave any alert as it is
+            let Some(&(p75, samples)) = current[device].get(&lookup) else {
+                open.remove(key);
+                continue;
+            };
+            let baseline_p75 = baseline[device].get(&lookup).map(|(p75, _)| *p75);
+            check.checked += 1;
+
+            let reason = breach_reason(budget, p75, baseline_p75);
+            let (sql, event) = match (reason, open.remove(key)) {
+                (Some(reason), Some(alert)) => {
+                    sqlx::query(
+                        "UPDATE web_vitals_alerts SET budget = $2, p75 = $3, baseline_p75 = $4, \
+                         samples = $5, reason = $6, checked_at = NOW() WHERE id = $1",
+                    )
+                    .bind(alert.id)
+                    .bind(budget.p75)
+                    .bind(p75)
+                    .bind(baseline_p75)
+                    .bind(samples)
+                    .bind(reason)
+                    .execute(&self.pool)
+                    .await
+                    .map_err(db)?;
+                    continue;
+                }
+                (Some(reason), None) => {
+                    check.opened += 1;
+                    (
+                        sqlx::query_as::<_, VitalsAlert>(
+                            "INSERT INTO web_vitals_alerts \
+                             (template, device, metric, budget, p75, baseline_p75, samples, reason) \
+                             VALUES ($1, $2, $3, $4, $5, $6, $7, $8) RETURNING *",
+                        )
+                        .bind(template)
+                        .bind(device.map(|d| d.as_str()))
+                        .bind(metric.as_str())
+                        .bind(budget.p75)
+                        .bind(p75)
+                        .bind(baseline_p75)
+                        .bind(samples)
+                        .bind(reason),
+                        BUDGET_EXCEEDED_EVENT,
+                    )
+                }
+                (None, Some(alert)) => {
+                    check.resolved += 1;
+                    (
+                        sqlx::query_as::<_, VitalsAlert>(
+                            "UPDATE web_vitals_alerts SET p75 = $2, baseline_p75 = $3, samples = $4, \
+                             checked_at = NOW(), resolved_at = NOW() WHERE id = $1 RETURNING *",
+                        )
+                        .bind(alert.id)
+                        .bind(p75)
+                        .bind(baseline_p75)
+                        .bind(samples),
+                        BUDGET_RECOVERED_EVENT,
+                    )
+                }
+                (None, None) => continue,
+            };
+            let alert = sql.fetch_one(&self.pool).await.map_err(db)?;
+            self.publish(event, &alert).await;
+        }
+
+        // Budgets removed since: their alerts are closed without notice
+        let orphaned: Vec<Uuid> = open
+            .values()
+            .filter(|alert| {
+                VitalMetric::from_name(&alert.metric).is_some_and(|metric| {
+                    let device = alert.device.as_deref().and_then(Device::from_name);
+                    !budgets.contains_key(&(alert.template.clone(), metric, device))
+                })
+            })
+            .map(|alert| alert.id)
+            .collect();
+        if !orphaned.is_empty() {
+            let closed =
+                sqlx::query("UPDATE web_vitals_alerts SET resolved_at = NOW() WHERE id = ANY($1)")
+                    .bind(&orphaned)
+                    .execute(&self.pool)
+                    .await
+                    .map_err(db)?;
+            check.resolved += closed.rows_affected() as u32;
+        }
+        Ok(check)
+    }
+
+    async fn publish(&self, event_type: &str, alert: &VitalsAlert) {
+        let Some(events) = &self.events else {
+            return;
+        };
+        let payload = match serde_json::to_value(alert) {
+            Ok(payload) => payload,
+            Err(e) => {
+                tracing::warn!(alert_id = %alert.id, "Failed to encode budget alert: {}", e);
+                return;
+            }
+        };
+        if let Err(e) = events.publish(DomainEvent::new(event_type, payload)).await {
+            tracing::warn!(alert_id = %alert.id, error = %e, "Failed to publish budget alert");
+        }
+    }
+}
+
+/// Hourly check of the performance budgets
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EvaluateWebVitalsBudgetsJob {}
+
+impl JobPayload for EvaluateWebVitalsBudgetsJob {
+    fn job_type() -> &'static str {
+        "evaluate_web_vitals_budgets"
+    }
+
+    // The next run checks again
+    fn max_attempts() -> u32 {
+        1
+    }
+}
+
+/// Handler for [`EvaluateWebVitalsBudgetsJob`]
+#[derive(Clone)]
+pub struct EvaluateWebVitalsBudgetsHandler {
+    web_vitals: Arc<WebVitalsService>,
+}
+
+impl EvaluateWebVitalsBudgetsHandler {
+    pub fn new(web_vitals: Arc<WebVitalsService>) -> Self {
+        Self { web_vitals }
+    }
+}
+
+#[async_trait]
+impl JobHandler for EvaluateWebVitalsBudgetsHandler {
+    type Payload = EvaluateWebVitalsBudgetsJob;
+
+    async fn handle(&self, _payload: Self::Payload) -> Result<()> {
+        let check = self.web_vitals.check_budgets().await?;
+        if check.opened > 0 || check.resolved > 0 {
+            tracing::info!(
+                checked = check.checked,
+                opened = check.opened,
+                resolved = check.resolved,
+                "Performance budgets checked"
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentile_within_bucket_error() {
+        let mut histogram = vec![0i64; HISTOGRAM_BUCKETS];
+        for value in (1..=100).map(|i| i as f64 * 40.0) {
+            histogram[bucket_of(VitalMetric::Lcp, value)] += 1;
+        }
+        let p75 = percentile(VitalMetric::Lcp, &histogram, 0.75).unwrap();
+        assert!((p75 - 3_000.0).abs() <= 3_000.0 * 0.05, "p75 was {}", p75);
+        assert_eq!(percentile(VitalMetric::Lcp, &[0; 4], 0.75), None);
+
+        // Layout shifts are bucketed in thousandths; no shift at all is 0
+        let mut histogram = vec![0i64; HISTOGRAM_BUCKETS];
+        histogram[bucket_of(VitalMetric::Cls, 0.0)] += 3;
+        histogram[bucket_of(VitalMetric::Cls, 0.2)] += 1;
+        assert_eq!(percentile(VitalMetric::Cls, &histogram, 0.75), Some(0.0));
+        let p90 = percentile(VitalMetric::Cls, &histogram, 0.9).unwrap();
+        assert!((p90 - 0.2).abs() <= 0.01, "p90 was {}", p90);
+
+        assert_eq!(bucket_of(VitalMetric::Inp, 1e9), HISTOGRAM_BUCKETS - 1);
+    }
+
+    #[test]
+    fn test_paths_templates_and_devices() {
+        assert_eq!(
+            normalize_path("/post/hello/?utm=x#top").as_deref(),
+            Some("/post/hello")
+        );
+        assert_eq!(normalize_path("https://example.com/page/about"), None);
+        assert_eq!(normalize_path("javascript:alert(1)"), None);
+        assert_eq!(normalize_path("//evil.example/x"), None);
+
+        let site = "https://example.com";
+        assert_eq!(
+            beacon_path("/page/about/", site).as_deref(),
+            Some("/page/about")
+        );
+        assert_eq!(
+            beacon_path("https://example.com", site).as_deref(),
+            Some("/")
+        );
+        assert_eq!(
+            beacon_path("https://EXAMPLE.com:443/page/about?x=1", site).as_deref(),
+            Some("/page/about")
+        );
+        assert_eq!(beacon_path("https://evil.example/page/about", site), None);
+        assert_eq!(
+            beacon_path("https://example.com:8443/page/about", site),
+            None
+        );
+        assert_eq!(beacon_path("https://example.com@evil.example/", site), None);
+        assert_eq!(beacon_path("ftp://example.com/page/about", site), None);
+
+        assert_eq!(template_for_path("/"), "home");
+        assert_eq!(template_for_path("/post/hello"), "post");
+        assert_eq!(template_for_path("/2024/05"), "archive");
+        assert_eq!(template_for_path("/tag/rust"), "archive");
+        assert_eq!(template_for_path("/2024"), "other");
+
+        assert_eq!(
+            Device::from_user_agent("Mozilla/5.0 (iPhone; CPU iPhone OS 17_0) Mobile/15E148"),
+            Device::Mobile
+        );
+        assert_eq!(
+            Device::from_user_agent("Mozilla/5.0 (Linux; Android 14; SM-X710) Safari/537.36"),
+            Device::Tablet
+        );
+        assert_eq!(
+            Device::from_user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) Chrome/126.0"),
+            Device::Desktop
+        );
+    }
+
+    #[test]
+    fn test_budgets() {
+        let mut config = WebVitalsConfig::default();
+        assert!(config.validate().is_ok());
+        config.budgets.push(VitalsBudget {
+            template: Some("post".to_string()),
+            metric: VitalMetric::Lcp,
+            device: None,
+            p75: 3_000.0,
+            max_regression_percent: Some(10.0),
+        });
+        assert!(config.validate().is_ok());
+
+        // A template's own budget replaces the default one
+        let budgets = config.effective_budgets();
+        let post = budgets[&("post".to_string(), VitalMetric::Lcp, None)];
+        assert_eq!(post.p75, 3_000.0);
+        assert_eq!(
+            budgets[&("home".to_string(), VitalMetric::Lcp, None)].p75,
+            2_500.0
+        );
+        assert_eq!(budgets.len(), TEMPLATES.len() * 3);
+
+        assert_eq!(breach_reason(post, 3_100.0, None), Some("over_budget"));
+        assert_eq!(
+            breach_reason(post, 2_800.0, Some(2_500.0)),
+            Some("regressed")
+        );
+        assert_eq!(breach_reason(post, 2_700.0, Some(2_500.0)), None);
+
+        let mut duplicate = config.clone();
+        duplicate.budgets.push(duplicate.budgets[3].clone());
+        assert!(duplicate.validate().is_err());
+        config.budgets[3].template = Some("landing".to_string());
+        assert!(config.validate().is_err());
+    }
+}
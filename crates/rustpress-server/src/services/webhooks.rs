@@ -0,0 +1,1044 @@
+//! Outbound Webhooks
+//!
+//! Users register endpoint URLs with the event types they want, as exact
+//! types (`post.published`) or patterns (`post.*`, `*`), and every matching
+//! event published on the bus is posted to them:
+//!
+//! - each event is one delivery per endpoint, stored with the exact body
+//!   that is sent and posted by a [`DeliverWebhookJob`] on the `webhooks`
+//!   queue. Failed attempts (network errors and non-2xx responses) are
+//!   retried with exponential backoff until [`MAX_DELIVERY_ATTEMPTS`]
+//! - every attempt is logged with its response status, the start of the
+//!   response body and how long it took
+//! - requests carry [`SIGNATURE_HEADER`] with an HMAC-SHA256 of
+//!   `{timestamp}.{body}` under the endpoint's secret, so receivers can
+//!   check where a request came from and refuse replays. Endpoints may
+//!   also ask for an RFC 9421 signature by the site's newest outbound key
+//!   (see [`super::http_signatures`]), checked against the published keys
+//! - any delivery can be sent again by hand; the redelivery is a new
+//!   delivery pointing at the original
+//!
+//! Endpoints of a network site only get that site's events. Endpoint URLs
+//! must resolve to public addresses, which is checked again before every
+//! attempt. Internal `change.*` events, republished on every node, are not
+//! sent, nor are the job events of deliveries themselves.
+
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use async_trait::async_trait;
+use base64::Engine as _;
+use chrono::{DateTime, Utc};
+use ring::hmac;
+use rustpress_core::error::{Error, Result};
+use rustpress_core::http::HttpClient;
+use rustpress_core::tenant::current_site;
+use rustpress_events::{DomainEvent, EventBus};
+use rustpress_jobs::{Backoff, JobHandler, JobPayload, JobQueue, RetryPolicy};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sqlx::{FromRow, PgPool};
+use std::fmt::Write as _;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, RwLock};
+use uuid::Uuid;
+
+use super::http_signatures::HttpSignatureService;
+use super::wordpress_import::is_public_ip;
+use crate::ws::manager::{channel_matches, valid_channel};
+
+/// Queue deliveries are posted from
+pub const WEBHOOK_QUEUE: &str = "webhooks";
+
+/// Header carrying `t=<unix time>,v1=<hex HMAC-SHA256>`
+pub const SIGNATURE_HEADER: &str = "X-RustPress-Signature";
+
+/// Header carrying the event type
+pub const EVENT_HEADER: &str = "X-RustPress-Event";
+
+/// Header carrying the delivery id, the same on every attempt
+pub const DELIVERY_HEADER: &str = "X-RustPress-Delivery";
+
+/// Attempts of one delivery before it is given up on
+pub const MAX_DELIVERY_ATTEMPTS: u32 = 8;
+
+/// Endpoints one user may register
+const MAX_ENDPOINTS_PER_USER: i64 = 20;
+
+/// Event types or patterns of one endpoint
+const MAX_EVENT_TYPES: usize = 50;
+
+/// Longest endpoint URL
+const MAX_URL_LENGTH: usize = 2048;
+
+/// Longest endpoint description
+const MAX_DESCRIPTION_LENGTH: usize = 500;
+
+/// How long an endpoint has to answer
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Response bytes kept in the attempt log
+const RESPONSE_BODY_LIMIT: usize = 2048;
+
+/// How long the list of active endpoints is cached; other nodes' changes
+/// show up after at most this long
+const ENDPOINT_CACHE_TTL: Duration = Duration::from_secs(30);
+
+/// Random bytes of an endpoint secret
+const SECRET_BYTES: usize = 32;
+
+/// Prefix of endpoint secrets, so they are recognisable when leaked
+const SECRET_PREFIX: &str = "whsec_";
+
+/// Type of the event sent by a ping
+pub const PING_EVENT: &str = "webhook.ping";
+
+/// A registered endpoint
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct WebhookEndpoint {
+    pub id: Uuid,
+    pub owner_id: Uuid,
+    pub site_id: Option<Uuid>,
+    pub url: String,
+    pub description: Option<String>,
+    pub event_types: Vec<String>,
+    #[serde(skip_serializing)]
+    pub secret: String,
+    /// Whether deliveries also carry an HTTP message signature
+    pub http_signature: bool,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl WebhookEndpoint {
+    /// Whether the endpoint wants an event
+    pub fn wants(&self, event: &DomainEvent) -> bool {
+        self.is_active
+            && (self.site_id.is_none() || self.site_id == event.tenant_id)
+            && self
+                .event_types
+                .iter()
+                .any(|pattern| channel_matches(pattern, &event.event_type))
+    }
+}
+
+/// A new endpoint; the secret is only shown here
+#[derive(Debug, Serialize)]
+pub struct CreatedWebhookEndpoint {
+    #[serde(flatten)]
+    pub endpoint: WebhookEndpoint,
+    pub secret: String,
+}
+
+/// Endpoint registration
+#[derive(Debug, Deserialize)]
+pub struct WebhookEndpointInput {
+    pub url: String,
+    pub description: Option<String>,
+    pub event_types: Vec<String>,
+    #[serde(default)]
+    pub http_signature: bool,
+}
+
+/// Endpoint changes; missing fields are kept
+#[derive(Debug, Default, Deserialize)]
+pub struct WebhookEndpointUpdate {
+    pub url: Option<String>,
+    pub description: Option<String>,
+    pub event_types: Option<Vec<String>>,
+    pub http_signature: Option<bool>,
+    pub is_active: Option<bool>,
+}
+
+/// State of a delivery
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeliveryStatus {
+    Pending,
+    Succeeded,
+    Failed,
+}
+
+impl DeliveryStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Succeeded => "succeeded",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+/// One event sent, or to be sent, to one endpoint
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct WebhookDelivery {
+    pub id: Uuid,
+    pub endpoint_id: Uuid,
+    pub event_id: Option<Uuid>,
+    pub event_type: String,
+    pub payload: Value,
+    pub status: String,
+    pub attempts: i32,
+    pub response_status: Option<i32>,
+    pub last_error: Option<String>,
+    pub redelivery_of: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+/// One request made for a delivery
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct WebhookDeliveryAttempt {
+    pub id: Uuid,
+    pub delivery_id: Uuid,
+    pub attempt: i32,
+    pub response_status: Option<i32>,
+    pub response_body: Option<String>,
+    pub error: Option<String>,
+    pub duration_ms: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A delivery with its attempt log
+#[derive(Debug, Serialize)]
+pub struct WebhookDeliveryDetail {
+    #[serde(flatten)]
+    pub delivery: WebhookDelivery,
+    pub attempts_log: Vec<WebhookDeliveryAttempt>,
+}
+
+/// Delivery list filter
+#[derive(Debug, Default, Deserialize)]
+pub struct DeliveryQuery {
+    pub status: Option<DeliveryStatus>,
+    pub limit: Option<i64>,
+}
+
+/// Outcome of one attempt
+struct AttemptResult {
+    response_status: Option<i32>,
+    response_body: Option<String>,
+    error: Option<String>,
+    duration_ms: i32,
+}
+
+impl AttemptResult {
+    fn failed(error: impl ToString, duration_ms: i32) -> Self {
+        Self {
+            response_status: None,
+            response_body: None,
+            error: Some(error.to_string()),
+            duration_ms,
+        }
+    }
+
+    fn succeeded(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Webhook endpoints and their deliveries
+pub struct WebhookService {
+    pool: PgPool,
+    http: HttpClient,
+    jobs: Arc<JobQueue>,
+    /// Signs deliveries to endpoints that ask for it
+    signatures: Arc<HttpSignatureService>,
+    active: RwLock<Option<(Instant, Arc<Vec<WebhookEndpoint>>)>>,
+}
+
+impl WebhookService {
+    pub fn new(
+        pool: PgPool,
+        http: HttpClient,
+        jobs: Arc<JobQueue>,
+        signatures: Arc<HttpSignatureService>,
+    ) -> Self {
+        Self {
+            pool,
+            http,
+            jobs,
+            signatures,
+            active: RwLock::new(None),
+        }
+    }
+
+    /// Endpoints of one owner, or all of them
+    pub async fn list(&self, owner_id: Option<Uuid>) -> Result<Vec<WebhookEndpoint>> {
+        sqlx::query_as::<_, WebhookEndpoint>(
+            r#"
+            SELECT * FROM webhook_endpoints
+            WHERE ($1::uuid IS NULL OR owner_id = $1)
+              AND ($2::uuid IS NULL OR site_id = $2)
+            ORDER BY created_at
+            "#,
+        )
+        .bind(owner_id)
+        .bind(current_site())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to list webhook endpoints", e))
+    }
+
+    pub async fn get(&self, id: Uuid) -> Result<WebhookEndpoint> {
+        sqlx::query_as::<_, WebhookEndpoint>(
+            "SELECT * FROM webhook_endpoints WHERE id = $1 AND ($2::uuid IS NULL OR site_id = $2)",
+        )
+        .bind(id)
+        .bind(current_site())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load webhook endpoint", e))?
+        .ok_or_else(|| Error::not_found("Webhook endpoint", id))
+    }
+
+    /// Register an endpoint, generating its signing secret
+    pub async fn create(
+        &self,
+        owner_id: Uuid,
+        input: WebhookEndpointInput,
+    ) -> Result<CreatedWebhookEndpoint> {
+        let (url, _) = check_endpoint_url(&input.url).await?;
+        let event_types = check_event_types(input.event_types)?;
+        let description = check_description(input.description)?;
+
+        let count: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM webhook_endpoints WHERE owner_id = $1")
+                .bind(owner_id)
+                .fetch_one(&self.pool)
+                .await
+                .map_err(|e| Error::database_with_source("Failed to count webhook endpoints", e))?;
+        if count >= MAX_ENDPOINTS_PER_USER {
+            return Err(Error::invalid_input(
+                "url",
+                format!(
+                    "At most {} webhook endpoints can be registered",
+                    MAX_ENDPOINTS_PER_USER
+                ),
+            ));
+        }
+
+        let secret = generate_secret();
+        let endpoint = sqlx::query_as::<_, WebhookEndpoint>(
+            r#"
+            INSERT INTO webhook_endpoints
+                (owner_id, site_id, url, description, event_types, secret, http_signature)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING *
+            "#,
+        )
+        .bind(owner_id)
+        .bind(current_site())
+        .bind(url)
+        .bind(description)
+        .bind(&event_types)
+        .bind(&secret)
+        .bind(input.http_signature)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to create webhook endpoint", e))?;
+
+        self.invalidate().await;
+        Ok(CreatedWebhookEndpoint { endpoint, secret })
+    }
+
+    pub async fn update(&self, id: Uuid, update: WebhookEndpointUpdate) -> Result<WebhookEndpoint> {
+        let mut endpoint = self.get(id).await?;
+        if let Some(url) = update.url {
+            endpoint.url = check_endpoint_url(&url).await?.0;
+        }
+        if let Some(event_types) = update.event_types {
+            endpoint.event_types = check_event_types(event_types)?;
+        }
+        if update.description.is_some() {
+            endpoint.description = check_description(update.description)?;
+        }
+        if let Some(http_signature) = update.http_signature {
+            endpoint.http_signature = http_signature;
+        }
+        if let Some(is_active) = update.is_active {
+            endpoint.is_active = is_active;
+        }
+
+        let endpoint = sqlx::query_as::<_, WebhookEndpoint>(
+            r#"
+            UPDATE webhook_endpoints
+            SET url = $2, description = $3, event_types = $4, http_signature = $5,
+                is_active = $6, updated_at = NOW()
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(&endpoint.url)
+        .bind(&endpoint.description)
+        .bind(&endpoint.event_types)
+        .bind(endpoint.http_signature)
+        .bind(endpoint.is_active)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to update webhook endpoint", e))?;
+
+        self.invalidate().await;
+        Ok(endpoint)
+    }
+
+    /// Replace an endpoint's secret; requests are signed with the new one
+    /// from the next attempt
+    pub async fn rotate_secret(&self, id: Uuid) -> Result<CreatedWebhookEndpoint> {
+        self.get(id).await?;
+        let secret = generate_secret();
+        let endpoint = sqlx::query_as::<_, WebhookEndpoint>(
+            "UPDATE webhook_endpoints SET secret = $2, updated_at = NOW() WHERE id = $1 RETURNING *",
+        )
+        .bind(id)
+        .bind(&secret)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to rotate webhook secret", e))?;
+
+        self.invalidate().await;
+        Ok(CreatedWebhookEndpoint { endpoint, secret })
+    }
+
+    /// Remove an endpoint along with its deliveries
+    pub async fn delete(&self, id: Uuid) -> Result<()> {
+        self.get(id).await?;
+        sqlx::query("DELETE FROM webhook_endpoints WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to delete webhook endpoint", e))?;
+
+        self.invalidate().await;
+        Ok(())
+    }
+
+    /// Newest deliveries to an endpoint
+    pub async fn deliveries(
+        &self,
+        endpoint_id: Uuid,
+        query: &DeliveryQuery,
+    ) -> Result<Vec<WebhookDelivery>> {
+        sqlx::query_as::<_, WebhookDelivery>(
+            r#"
+            SELECT * FROM webhook_deliveries
+            WHERE endpoint_id = $1 AND ($2::varchar IS NULL OR status = $2)
+            ORDER BY created_at DESC
+            LIMIT $3
+            "#,
+        )
+        .bind(endpoint_id)
+        .bind(query.status.map(|s| s.as_str()))
+        .bind(query.limit.unwrap_or(50).clamp(1, 100))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to list webhook deliveries", e))
+    }
+
+    pub async fn delivery(&self, id: Uuid) -> Result<WebhookDelivery> {
+        sqlx::query_as::<_, WebhookDelivery>("SELECT * FROM webhook_deliveries WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to load webhook delivery", e))?
+            .ok_or_else(|| Error::not_found("Webhook delivery", id))
+    }
+
+    /// A delivery with every attempt made for it
+    pub async fn delivery_detail(&self, id: Uuid) -> Result<WebhookDeliveryDetail> {
+        let delivery = self.delivery(id).await?;
+        let attempts_log = sqlx::query_as::<_, WebhookDeliveryAttempt>(
+            "SELECT * FROM webhook_delivery_attempts WHERE delivery_id = $1 ORDER BY attempt",
+        )
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load webhook delivery attempts", e))?;
+        Ok(WebhookDeliveryDetail {
+            delivery,
+            attempts_log,
+        })
+    }
+
+    /// Send a delivery again, as a new delivery with the same body
+    pub async fn redeliver(&self, id: Uuid) -> Result<WebhookDelivery> {
+        let original = self.delivery(id).await?;
+        let endpoint = self.get(original.endpoint_id).await?;
+        if !endpoint.is_active {
+            return Err(Error::invalid_input(
+                "endpoint_id",
+                "The webhook endpoint is disabled",
+            ));
+        }
+
+        let delivery = self
+            .insert_delivery(
+                endpoint.id,
+                original.event_id,
+                &original.event_type,
+                &original.payload,
+                Some(original.id),
+            )
+            .await?;
+        self.enqueue(delivery.id).await?;
+        Ok(delivery)
+    }
+
+    /// Send a `webhook.ping` event to an endpoint, whatever types it wants
+    pub async fn ping(&self, endpoint_id: Uuid) -> Result<WebhookDelivery> {
+        let endpoint = self.get(endpoint_id).await?;
+        let event = DomainEvent::new(PING_EVENT, json!({ "endpoint_id": endpoint.id }));
+        let delivery = self
+            .insert_delivery(
+                endpoint.id,
+                Some(event.id),
+                &event.event_type,
+                &request_body(&event),
+                None,
+            )
+            .await?;
+        self.enqueue(delivery.id).await?;
+        Ok(delivery)
+    }
+
+    /// Queue a delivery of an event to every endpoint that wants it
+    pub async fn fan_out(&self, event: &DomainEvent) -> Result<usize> {
+        let endpoints = self.active_endpoints().await?;
+        let mut body = None;
+        let mut queued = 0;
+        for endpoint in endpoints.iter().filter(|e| e.wants(event)) {
+            let body = body.get_or_insert_with(|| request_body(event));
+            let delivery = self
+                .insert_delivery(endpoint.id, Some(event.id), &event.event_type, body, None)
+                .await?;
+            self.enqueue(delivery.id).await?;
+            queued += 1;
+        }
+        Ok(queued)
+    }
+
+    /// Send every event published on the bus to the endpoints that want it
+    pub fn spawn(self: &Arc<Self>, bus: &EventBus) -> tokio::task::JoinHandle<()> {
+        let webhooks = Arc::downgrade(self);
+        let mut events = bus.subscribe_broadcast();
+        tokio::spawn(async move {
+            loop {
+                let event = match events.recv().await {
+                    Ok(event) => event,
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        tracing::warn!(
+                            missed,
+                            "Webhooks fell behind the event bus, events skipped"
+                        );
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return,
+                };
+                if !is_deliverable(&event) {
+                    continue;
+                }
+                let Some(webhooks) = webhooks.upgrade() else {
+                    return;
+                };
+                if let Err(e) = webhooks.fan_out(&event).await {
+                    tracing::warn!(
+                        event_id = %event.id,
+                        event_type = %event.event_type,
+                        "Failed to queue webhook deliveries: {}",
+                        e
+                    );
+                }
+            }
+        })
+    }
+
+    /// Make one attempt at a delivery
+    ///
+    /// Fails, so the job is retried, while the endpoint does not accept it
+    /// and attempts are left; the last failed attempt marks it failed.
+    pub async fn deliver(&self, delivery_id: Uuid) -> Result<()> {
+        let delivery = self.delivery(delivery_id).await?;
+        if delivery.status != DeliveryStatus::Pending.as_str() {
+            return Ok(());
+        }
+        let endpoint =
+            sqlx::query_as::<_, WebhookEndpoint>("SELECT * FROM webhook_endpoints WHERE id = $1")
+                .bind(delivery.endpoint_id)
+                .fetch_one(&self.pool)
+                .await
+                .map_err(|e| Error::database_with_source("Failed to load webhook endpoint", e))?;
+        if !endpoint.is_active {
+            return self
+                .finish(
+                    &delivery,
+                    DeliveryStatus::Failed,
+                    None,
+                    Some("Endpoint disabled"),
+                )
+                .await;
+        }
+
+        let attempt = delivery.attempts + 1;
+        let result = self.send(&endpoint, &delivery).await;
+        sqlx::query(
+            r#"
+            INSERT INTO webhook_delivery_attempts
+                (delivery_id, attempt, response_status, response_body, error, duration_ms)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+        )
+        .bind(delivery.id)
+        .bind(attempt)
+        .bind(result.response_status)
+        .bind(&result.response_body)
+        .bind(&result.error)
+        .bind(result.duration_ms)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to log webhook attempt", e))?;
+
+        let delivery = WebhookDelivery {
+            attempts: attempt,
+            ..delivery
+        };
+        if result.succeeded() {
+            return self
+                .finish(
+                    &delivery,
+                    DeliveryStatus::Succeeded,
+                    result.response_status,
+                    None,
+                )
+                .await;
+        }
+        let error = result.error.as_deref().unwrap_or_default();
+        if attempt as u32 >= MAX_DELIVERY_ATTEMPTS {
+            return self
+                .finish(
+                    &delivery,
+                    DeliveryStatus::Failed,
+                    result.response_status,
+                    Some(error),
+                )
+                .await;
+        }
+        self.finish(
+            &delivery,
+            DeliveryStatus::Pending,
+            result.response_status,
+            Some(error),
+        )
+        .await?;
+        Err(Error::ServiceUnavailable {
+            service: format!("webhook endpoint {} ({})", endpoint.url, error),
+        })
+    }
+
+    /// Post a delivery's body to its endpoint
+    async fn send(&self, endpoint: &WebhookEndpoint, delivery: &WebhookDelivery) -> AttemptResult {
+        let started = Instant::now();
+        let elapsed = |started: Instant| started.elapsed().as_millis().min(i32::MAX as u128) as i32;
+
+        let addrs = match check_endpoint_url(&endpoint.url).await {
+            Ok((_, addrs)) => addrs,
+            Err(e) => return AttemptResult::failed(e, 0),
+        };
+
+        let body = delivery.payload.to_string();
+        let timestamp = Utc::now().timestamp();
+        let request = self
+            .http
+            .post(&endpoint.url)
+            .timeout(REQUEST_TIMEOUT)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(
+                SIGNATURE_HEADER,
+                signature_header(&endpoint.secret, timestamp, &body),
+            )
+            .header(EVENT_HEADER, &delivery.event_type)
+            .header(DELIVERY_HEADER, delivery.id.to_string())
+            .body(body)
+            .build();
+        let mut request = match request {
+            Ok(request) => request,
+            Err(e) => return AttemptResult::failed(e.without_url(), 0),
+        };
+        if endpoint.http_signature {
+            match self.signatures.sign_request(&mut request).await {
+                Ok(true) => {}
+                Ok(false) => return AttemptResult::failed("No outbound signing key exists", 0),
+                Err(e) => return AttemptResult::failed(e, 0),
+            }
+        }
+
+        // Connect to the addresses checked above, so a DNS answer that
+        // changes in between cannot send the delivery elsewhere
+        let mut response = match self.http.execute_to(request, &addrs).await {
+            Ok(response) => response,
+            Err(e) => return AttemptResult::failed(e, elapsed(started)),
+        };
+        let status = response.status();
+        // Only the start of the body is kept, so stop reading there
+        let mut body = Vec::new();
+        while body.len() < RESPONSE_BODY_LIMIT {
+            match response.chunk().await {
+                Ok(Some(chunk)) => body.extend_from_slice(&chunk),
+                _ => break,
+            }
+        }
+        let text = String::from_utf8_lossy(&body);
+        AttemptResult {
+            response_status: Some(status.as_u16() as i32),
+            response_body: Some(truncate(&text, RESPONSE_BODY_LIMIT).to_string()),
+            error: (!status.is_success()).then(|| format!("Endpoint answered {}", status)),
+            duration_ms: elapsed(started),
+        }
+    }
+
+    async fn finish(
+        &self,
+        delivery: &WebhookDelivery,
+        status: DeliveryStatus,
+        response_status: Option<i32>,
+        error: Option<&str>,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE webhook_deliveries
+            SET status = $2, attempts = $3, response_status = $4, last_error = $5,
+                delivered_at = CASE WHEN $2 = 'succeeded' THEN NOW() ELSE delivered_at END
+            WHERE id = $1
+            "#,
+        )
+        .bind(delivery.id)
+        .bind(status.as_str())
+        .bind(delivery.attempts)
+        .bind(response_status)
+        .bind(error)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to update webhook delivery", e))?;
+        Ok(())
+    }
+
+    async fn insert_delivery(
+        &self,
+        endpoint_id: Uuid,
+        event_id: Option<Uuid>,
+        event_type: &str,
+        payload: &Value,
+        redelivery_of: Option<Uuid>,
+    ) -> Result<WebhookDelivery> {
+        sqlx::query_as::<_, WebhookDelivery>(
+            r#"
+            INSERT INTO webhook_deliveries (endpoint_id, event_id, event_type, payload, redelivery_of)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING *
+            "#,
+        )
+        .bind(endpoint_id)
+        .bind(event_id)
+        .bind(event_type)
+        .bind(payload)
+        .bind(redelivery_of)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to create webhook delivery", e))
+    }
+
+    async fn enqueue(&self, delivery_id: Uuid) -> Result<()> {
+        self.jobs
+            .dispatch(DeliverWebhookJob { delivery_id })
+            .await?;
+        Ok(())
+    }
+
+    async fn active_endpoints(&self) -> Result<Arc<Vec<WebhookEndpoint>>> {
+        if let Some((loaded_at, endpoints)) = &*self.active.read().await {
+            if loaded_at.elapsed() < ENDPOINT_CACHE_TTL {
+                return Ok(endpoints.clone());
+            }
+        }
+
+        let endpoints = Arc::new(
+            sqlx::query_as::<_, WebhookEndpoint>(
+                "SELECT * FROM webhook_endpoints WHERE is_active = TRUE",
+            )
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to load webhook endpoints", e))?,
+        );
+        *self.active.write().await = Some((Instant::now(), endpoints.clone()));
+        Ok(endpoints)
+    }
+
+    async fn invalidate(&self) {
+        *self.active.write().await = None;
+    }
+}
+
+/// Whether an event may be sent to endpoints at all
+fn is_deliverable(event: &DomainEvent) -> bool {
+    if event.event_type.starts_with("change.") {
+        return false;
+    }
+    // Announcing deliveries would deliver those announcements, and so on
+    !(event.event_type.starts_with("job.")
+        && event.payload.get("job_type").and_then(Value::as_str)
+            == Some(DeliverWebhookJob::job_type()))
+}
+
+/// Body posted for an event
+fn request_body(event: &DomainEvent) -> Value {
+    json!({
+        "id": event.id,
+        "type": event.event_type,
+        "occurred_at": event.occurred_at,
+        "site_id": event.tenant_id,
+        "data": event.payload,
+    })
+}
+
+/// `t=<timestamp>,v1=<signature>`, signing `{timestamp}.{body}`
+pub fn signature_header(secret: &str, timestamp: i64, body: &str) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    let tag = hmac::sign(&key, format!("{}.{}", timestamp, body).as_bytes());
+    let mut header = format!("t={},v1=", timestamp);
+    for byte in tag.as_ref() {
+        let _ = write!(header, "{:02x}", byte);
+    }
+    header
+}
+
+/// Check a webhook URL; returns it trimmed with the addresses checked, for
+/// deliveries to connect to
+async fn check_endpoint_url(url: &str) -> Result<(String, Vec<SocketAddr>)> {
+    let url = url.trim();
+    if url.len() > MAX_URL_LENGTH {
+        return Err(Error::invalid_input("url", "Webhook URL is too long"));
+    }
+    let parsed =
+        reqwest::Url::parse(url).map_err(|_| Error::invalid_input("url", "Invalid webhook URL"))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(Error::invalid_input("url", "Webhook URLs must use HTTP(S)"));
+    }
+    let host = parsed
+        .host_str()
+        .ok_or_else(|| Error::invalid_input("url", "Webhook URL has no host"))?;
+    let port = parsed.port_or_known_default().unwrap_or(80);
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.trim_matches(['[', ']']), port))
+        .await
+        .map_err(|e| Error::invalid_input("url", format!("Cannot resolve {}: {}", host, e)))?
+        .collect();
+    if addrs.is_empty() || addrs.iter().any(|addr| !is_public_ip(addr.ip())) {
+        return Err(Error::invalid_input(
+            "url",
+            format!("Webhook host {} is not a public address", host),
+        ));
+    }
+    Ok((url.to_string(), addrs))
+}
+
+fn check_event_types(event_types: Vec<String>) -> Result<Vec<String>> {
+    let mut checked: Vec<String> = Vec::with_capacity(event_types.len());
+    for event_type in event_types {
+        let event_type = event_type.trim().to_string();
+        if !valid_channel(&event_type) {
+            return Err(Error::invalid_input(
+                "event_types",
+                format!("'{}' is not an event type or pattern", event_type),
+            ));
+        }
+        if !checked.contains(&event_type) {
+            checked.push(event_type);
+        }
+    }
+    if checked.is_empty() {
+        return Err(Error::invalid_input(
+            "event_types",
+            "At least one event type is required",
+        ));
+    }
+    if checked.len() > MAX_EVENT_TYPES {
+        return Err(Error::invalid_input(
+            "event_types",
+            format!("At most {} event types are allowed", MAX_EVENT_TYPES),
+        ));
+    }
+    Ok(checked)
+}
+
+fn check_description(description: Option<String>) -> Result<Option<String>> {
+    let description = description
+        .map(|d| d.trim().to_string())
+        .filter(|d| !d.is_empty());
+    if description
+        .as_ref()
+        .is_some_and(|d| d.chars().count() > MAX_DESCRIPTION_LENGTH)
+    {
+        return Err(Error::invalid_input(
+            "description",
+            "Webhook description is too long",
+        ));
+    }
+    Ok(description)
+}
+
+fn generate_secret() -> String {
+    let mut bytes = [0u8; SECRET_BYTES];
+    OsRng.fill_bytes(&mut bytes);
+    format!(
+        "{}{}",
+        SECRET_PREFIX,
+        base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes)
+    )
+}
+
+fn truncate(text: &str, max: usize) -> &str {
+    if text.len() <= max {
+        return text;
+    }
+    let mut end = max;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+/// Post one webhook delivery
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeliverWebhookJob {
+    pub delivery_id: Uuid,
+}
+
+impl JobPayload for DeliverWebhookJob {
+    fn job_type() -> &'static str {
+        "deliver_webhook"
+    }
+
+    fn queue() -> &'static str {
+        WEBHOOK_QUEUE
+    }
+
+    fn max_attempts() -> u32 {
+        MAX_DELIVERY_ATTEMPTS
+    }
+
+    /// Endpoints are often down for a while, so back off up to six hours
+    fn retry_policy() -> RetryPolicy {
+        RetryPolicy::new(Self::max_attempts()).with_backoff(Backoff::Exponential {
+            base_secs: 30,
+            max_secs: 6 * 60 * 60,
+        })
+    }
+
+    fn timeout_secs() -> u64 {
+        60
+    }
+}
+
+/// Handler for [`DeliverWebhookJob`]
+#[derive(Clone)]
+pub struct DeliverWebhookHandler {
+    webhooks: Arc<WebhookService>,
+}
+
+impl DeliverWebhookHandler {
+    pub fn new(webhooks: Arc<WebhookService>) -> Self {
+        Self { webhooks }
+    }
+}
+
+#[async_trait]
+impl JobHandler for DeliverWebhookHandler {
+    type Payload = DeliverWebhookJob;
+
+    async fn handle(&self, payload: Self::Payload) -> Result<()> {
+        self.webhooks.deliver(payload.delivery_id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn endpoint(event_types: &[&str], site_id: Option<Uuid>) -> WebhookEndpoint {
+        WebhookEndpoint {
+            id: Uuid::new_v4(),
+            owner_id: Uuid::new_v4(),
+            site_id,
+            url: "https://hooks.example.com/rustpress".to_string(),
+            description: None,
+            event_types: event_types.iter().map(|t| t.to_string()).collect(),
+            secret: generate_secret(),
+            http_signature: false,
+            is_active: true,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_signature_header() {
+        let header = signature_header("secret", 1_700_000_000, "{}");
+        assert!(header.starts_with("t=1700000000,v1="));
+        assert_eq!(header.len(), "t=1700000000,v1=".len() + 64);
+        assert_eq!(header, signature_header("secret", 1_700_000_000, "{}"));
+        assert_ne!(header, signature_header("other", 1_700_000_000, "{}"));
+        assert_ne!(header, signature_header("secret", 1_700_000_001, "{}"));
+    }
+
+    #[test]
+    fn test_endpoint_wants_matching_events_of_its_site() {
+        let site = Uuid::new_v4();
+        let event = DomainEvent::new("post.published", json!({}));
+        let site_event = DomainEvent::new("post.published", json!({})).with_tenant(site);
+
+        assert!(endpoint(&["post.*"], None).wants(&event));
+        assert!(endpoint(&["post.*"], None).wants(&site_event));
+        assert!(!endpoint(&["comment.*"], None).wants(&event));
+        assert!(endpoint(&["*"], Some(site)).wants(&site_event));
+        assert!(!endpoint(&["*"], Some(site)).wants(&event));
+
+        let mut disabled = endpoint(&["*"], None);
+        disabled.is_active = false;
+        assert!(!disabled.wants(&event));
+    }
+
+    #[test]
+    fn test_own_job_events_are_not_delivered() {
+        assert!(is_deliverable(&DomainEvent::new(
+            "post.published",
+            json!({})
+        )));
+        assert!(!is_deliverable(&DomainEvent::new(
+            "change.posts",
+            json!({})
+        )));
+        assert!(!is_deliverable(&DomainEvent::new(
+            "job.failed",
+            json!({ "job_type": "deliver_webhook" })
+        )));
+        assert!(is_deliverable(&DomainEvent::new(
+            "job.failed",
+            json!({ "job_type": "send_email" })
+        )));
+    }
+
+    #[test]
+    fn test_check_event_types() {
+        assert_eq!(
+            check_event_types(vec![
+                "post.*".into(),
+                " post.* ".into(),
+                "user.created".into()
+            ])
+            .unwrap(),
+            vec!["post.*", "user.created"]
+        );
+        assert!(check_event_types(vec![]).is_err());
+        assert!(check_event_types(vec!["post.**".into()]).is_err());
+    }
+}
@@ -0,0 +1,939 @@
+//! Widget areas
+//!
+//! Themes declare widget areas in their manifest, under `widget_areas`,
+//! `widgetAreas` or `sidebars`. The areas are registered per theme the
+//! first time they are needed and again whenever the manifest changes:
+//!
+//! - areas the manifest drops are retired with their widgets, which come
+//!   back if a later version declares the area again
+//! - widgets placed before areas were registered move into the area whose
+//!   slug matches their sidebar
+//!
+//! Widgets are blocks, such as `core/paragraph`, or classic widgets (search,
+//! recent posts, categories, tag cloud, navigation, text and HTML). Areas
+//! render on the server with the wrapper markup the theme declares, and
+//! are cached per theme until a widget changes or the cache expires.
+
+use parking_lot::Mutex;
+use rustpress_content::{Block, BlockParser, BlockRenderer};
+use rustpress_core::error::{Error, Result};
+use rustpress_database::repository::widgets::{
+    RegisterArea, SaveWidget, WidgetAreaRow, WidgetRow, WidgetsRepository,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tera::escape_html;
+use uuid::Uuid;
+
+use super::menus::MenuService;
+use super::redirects::content_path;
+use super::render_service::{MenuData, MenuItemData, WidgetAreaData, WidgetData};
+use super::taxonomy::{Taxonomy, CATEGORY_TAXONOMY, TAG_TAXONOMY};
+use super::theme_service::ThemeService;
+
+/// How long rendered areas are reused; widgets listing posts and terms
+/// catch up with new content after this
+const RENDER_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// Longest widget title
+const MAX_TITLE: usize = 255;
+
+/// Most posts a recent posts widget lists
+const MAX_RECENT_POSTS: i64 = 20;
+
+/// Terms a tag cloud shows when its settings don't say
+const DEFAULT_CLOUD_SIZE: i64 = 45;
+
+/// Areas registered when the theme declares none
+const DEFAULT_AREAS: &[(&str, &str)] = &[
+    ("sidebar", "Main Sidebar"),
+    ("footer-1", "Footer Column 1"),
+    ("footer-2", "Footer Column 2"),
+    ("footer-3", "Footer Column 3"),
+];
+
+/// Classic widgets: type, name and description
+pub const CLASSIC_WIDGETS: &[(&str, &str, &str)] = &[
+    ("text", "Text", "Arbitrary text or HTML"),
+    ("recent_posts", "Recent Posts", "Display recent posts"),
+    ("categories", "Categories", "Display category list"),
+    ("tag_cloud", "Tag Cloud", "Display tag cloud"),
+    ("search", "Search", "Search form"),
+    ("html", "Custom HTML", "Custom HTML content"),
+    ("navigation", "Navigation Menu", "Display a menu"),
+];
+
+/// Older names of classic widgets
+const WIDGET_ALIASES: &[(&str, &str)] = &[("tags", "tag_cloud"), ("custom_html", "html")];
+
+const DEFAULT_BEFORE_WIDGET: &str = r#"<section id="{id}" class="widget {class}">"#;
+const DEFAULT_AFTER_WIDGET: &str = "</section>";
+const DEFAULT_BEFORE_TITLE: &str = r#"<h2 class="widget-title">"#;
+const DEFAULT_AFTER_TITLE: &str = "</h2>";
+
+/// A widget to create, or its new settings
+#[derive(Debug, Clone, Deserialize)]
+pub struct WidgetInput {
+    /// Slug of the area holding the widget; required on create, and kept
+    /// on update when left out
+    pub area: Option<String>,
+    /// Theme of the area, the active one by default
+    pub theme: Option<String>,
+    /// Block name or classic widget type
+    #[serde(alias = "type")]
+    pub widget_type: String,
+    pub title: Option<String>,
+    /// Inner HTML of blocks, or their inner blocks as JSON
+    pub content: Option<String>,
+    /// Block attributes or classic widget options
+    #[serde(default)]
+    pub settings: serde_json::Value,
+    #[serde(default = "default_active")]
+    pub is_active: bool,
+}
+
+fn default_active() -> bool {
+    true
+}
+
+impl WidgetInput {
+    fn normalize(self, area_id: Uuid) -> Result<SaveWidget> {
+        let widget_type = widget_type(&self.widget_type)?;
+        let title = non_empty(self.title);
+        if title
+            .as_ref()
+            .is_some_and(|t| t.chars().count() > MAX_TITLE)
+        {
+            return Err(Error::invalid_input(
+                "title",
+                "Widget titles are at most 255 characters",
+            ));
+        }
+        let settings = match self.settings {
+            serde_json::Value::Null => serde_json::json!({}),
+            settings @ serde_json::Value::Object(_) => settings,
+            _ => {
+                return Err(Error::invalid_input(
+                    "settings",
+                    "Widget settings must be an object",
+                ))
+            }
+        };
+        Ok(SaveWidget {
+            area_id,
+            widget_type,
+            title,
+            content: self.content,
+            settings,
+            is_active: self.is_active,
+        })
+    }
+}
+
+/// Widgets to put into an area, in order
+#[derive(Debug, Clone, Deserialize)]
+pub struct WidgetPlacement {
+    pub theme: Option<String>,
+    pub widgets: Vec<Uuid>,
+}
+
+/// An area with its widgets, as editors see it
+#[derive(Debug, Clone, Serialize)]
+pub struct WidgetArea {
+    #[serde(flatten)]
+    pub area: WidgetAreaRow,
+    pub widgets: Vec<WidgetRow>,
+}
+
+/// Posts and terms classic widgets list, loaded once per render
+#[derive(Default)]
+struct Listings {
+    recent_posts: Option<Vec<(String, String)>>,
+    terms: HashMap<&'static str, Vec<(String, String, i32)>>,
+    menus: Option<HashMap<String, MenuData>>,
+}
+
+/// Rendered areas of a theme
+type RenderedAreas = Arc<HashMap<String, WidgetAreaData>>;
+
+/// Widget area service
+pub struct WidgetService {
+    pool: PgPool,
+    themes: Arc<ThemeService>,
+    menus: Arc<MenuService>,
+    rendered: Mutex<HashMap<String, (Instant, RenderedAreas)>>,
+    /// Bumped on every change, so renders started before it are not cached
+    generation: AtomicU64,
+}
+
+impl WidgetService {
+    pub fn new(pool: PgPool, themes: Arc<ThemeService>, menus: Arc<MenuService>) -> Self {
+        Self {
+            pool,
+            themes,
+            menus,
+            rendered: Mutex::new(HashMap::new()),
+            generation: AtomicU64::new(0),
+        }
+    }
+
+    fn repo(&self) -> WidgetsRepository {
+        WidgetsRepository::new(self.pool.clone(), self.themes.site_id())
+    }
+
+    /// The requested theme, or the active one
+    pub async fn theme_id(&self, requested: Option<String>) -> Result<String> {
+        self.menus.theme_id(requested).await
+    }
+
+    /// Areas of a theme, registering them when the manifest changed
+    async fn registered_areas(&self, theme_id: &str) -> Result<Vec<WidgetAreaRow>> {
+        let declared = self.declared_areas(theme_id).await;
+        let stored = self.repo().areas(theme_id).await?;
+        let current: Vec<RegisterArea> = stored.iter().map(register_area).collect();
+        if current == declared {
+            return Ok(stored);
+        }
+        self.repo().register_areas(theme_id, &declared).await?;
+        self.invalidate();
+        self.repo().areas(theme_id).await
+    }
+
+    /// Areas of a theme with all of their widgets
+    pub async fn areas(&self, theme_id: &str) -> Result<Vec<WidgetArea>> {
+        let areas = self.registered_areas(theme_id).await?;
+        let ids: Vec<Uuid> = areas.iter().map(|a| a.id).collect();
+        let mut widgets = group_by_area(self.repo().widgets(&ids).await?);
+        Ok(areas
+            .into_iter()
+            .map(|area| WidgetArea {
+                widgets: widgets.remove(&area.id).unwrap_or_default(),
+                area,
+            })
+            .collect())
+    }
+
+    async fn area_row(&self, theme_id: &str, slug: &str) -> Result<WidgetAreaRow> {
+        self.registered_areas(theme_id)
+            .await?
+            .into_iter()
+            .find(|a| a.slug == slug)
+            .ok_or_else(|| Error::not_found("Widget area", slug))
+    }
+
+    /// An area of a theme with all of its widgets
+    pub async fn area(&self, theme_id: &str, slug: &str) -> Result<WidgetArea> {
+        let area = self.area_row(theme_id, slug).await?;
+        let widgets = self.repo().widgets(&[area.id]).await?;
+        Ok(WidgetArea { area, widgets })
+    }
+
+    pub async fn widget(&self, id: Uuid) -> Result<WidgetRow> {
+        self.repo()
+            .find_widget(id)
+            .await?
+            .ok_or_else(|| Error::not_found("Widget", id.to_string()))
+    }
+
+    /// Add a widget at the end of an area
+    pub async fn create(&self, input: WidgetInput) -> Result<WidgetRow> {
+        let slug = non_empty(input.area.clone())
+            .ok_or_else(|| Error::invalid_input("area", "A widget needs an area"))?;
+        let theme_id = self.theme_id(input.theme.clone()).await?;
+        let area = self.area_row(&theme_id, &slug).await?;
+        let widget = self
+            .repo()
+            .create_widget(&input.normalize(area.id)?)
+            .await?;
+        self.invalidate();
+        Ok(widget)
+    }
+
+    /// Save a widget; moving it to another area puts it at the end
+    pub async fn update(&self, id: Uuid, input: WidgetInput) -> Result<WidgetRow> {
+        let current = self.widget(id).await?;
+        let area_id = match non_empty(input.area.clone()) {
+            Some(slug) => {
+                let theme_id = self.theme_id(input.theme.clone()).await?;
+                self.area_row(&theme_id, &slug).await?.id
+            }
+            None => current
+                .area_id
+                .ok_or_else(|| Error::invalid_input("area", "The widget has no area"))?,
+        };
+        let widget = self
+            .repo()
+            .update_widget(id, &input.normalize(area_id)?)
+            .await?
+            .ok_or_else(|| Error::not_found("Widget", id.to_string()))?;
+        self.invalidate();
+        Ok(widget)
+    }
+
+    pub async fn delete(&self, id: Uuid) -> Result<()> {
+        if !self.repo().delete_widget(id).await? {
+            return Err(Error::not_found("Widget", id.to_string()));
+        }
+        self.invalidate();
+        Ok(())
+    }
+
+    /// Put widgets into an area in the given order. Widgets of the area
+    /// left out keep their place after the listed ones.
+    pub async fn place(
+        &self,
+        theme_id: &str,
+        slug: &str,
+        widget_ids: &[Uuid],
+    ) -> Result<WidgetArea> {
+        let area = self.area_row(theme_id, slug).await?;
+        if widget_ids.iter().collect::<HashSet<_>>().len() != widget_ids.len() {
+            return Err(Error::invalid_input("widgets", "Widgets are listed once"));
+        }
+        let mut ids = widget_ids.to_vec();
+        let rest = self
+            .repo()
+            .widgets(&[area.id])
+            .await?
+            .into_iter()
+            .map(|w| w.id)
+            .filter(|id| !widget_ids.contains(id));
+        ids.extend(rest);
+
+        let placed = self.repo().place_widgets(area.id, &ids).await?;
+        self.invalidate();
+        if placed < ids.len() as u64 {
+            return Err(Error::invalid_input(
+                "widgets",
+                "Some widgets do not exist or belong to another site",
+            ));
+        }
+        self.area(theme_id, slug).await
+    }
+
+    /// Rendered areas of a theme for its templates, keyed by slug
+    pub async fn theme_areas(&self, theme_id: &str) -> Result<HashMap<String, WidgetAreaData>> {
+        // Sites of a network share theme ids but not widgets
+        let key = match self.themes.site_id() {
+            Some(site) => format!("{}#{}", theme_id, site),
+            None => theme_id.to_string(),
+        };
+        if let Some((rendered_at, areas)) = self.rendered.lock().get(&key) {
+            if rendered_at.elapsed() < RENDER_CACHE_TTL {
+                return Ok(areas.as_ref().clone());
+            }
+        }
+
+        let generation = self.generation.load(Ordering::Acquire);
+        let areas = Arc::new(self.render_areas(theme_id).await?);
+        if self.generation.load(Ordering::Acquire) == generation {
+            let mut rendered = self.rendered.lock();
+            rendered.retain(|_, (rendered_at, _)| rendered_at.elapsed() < RENDER_CACHE_TTL);
+            rendered.insert(key, (Instant::now(), areas.clone()));
+        }
+        Ok(areas.as_ref().clone())
+    }
+
+    /// One rendered area of a theme
+    pub async fn rendered_area(&self, theme_id: &str, slug: &str) -> Result<WidgetAreaData> {
+        self.theme_areas(theme_id)
+            .await?
+            .remove(slug)
+            .ok_or_else(|| Error::not_found("Widget area", slug))
+    }
+
+    /// Drop rendered areas, after widgets or menus change
+    pub fn invalidate(&self) {
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        self.rendered.lock().clear();
+    }
+
+    async fn render_areas(&self, theme_id: &str) -> Result<HashMap<String, WidgetAreaData>> {
+        let areas = self.areas(theme_id).await?;
+        let mut listings = Listings::default();
+        let renderer = BlockRenderer::new();
+
+        let mut rendered = HashMap::new();
+        for WidgetArea { area, widgets } in areas {
+            let mut data = Vec::new();
+            for widget in widgets.into_iter().filter(|w| w.is_active) {
+                let content = self
+                    .widget_content(theme_id, &widget, &renderer, &mut listings)
+                    .await;
+                data.push(WidgetData {
+                    id: widget.id.to_string(),
+                    widget_type: widget.widget_type,
+                    title: widget.title,
+                    content,
+                    settings: widget.settings,
+                });
+            }
+            let html = area_html(&area, &data);
+            rendered.insert(
+                area.slug.clone(),
+                WidgetAreaData {
+                    slug: area.slug,
+                    name: area.name,
+                    description: area.description,
+                    widgets: data,
+                    html,
+                },
+            );
+        }
+        Ok(rendered)
+    }
+
+    /// HTML of a widget's body. Widgets whose listings fail to load render
+    /// empty rather than failing the page.
+    async fn widget_content(
+        &self,
+        theme_id: &str,
+        widget: &WidgetRow,
+        renderer: &BlockRenderer,
+        listings: &mut Listings,
+    ) -> String {
+        let content = widget.content.clone().unwrap_or_default();
+        let settings = &widget.settings;
+        let result = match widget.widget_type.as_str() {
+            "search" => Ok(search_form(settings)),
+            "text" | "html" => Ok(content),
+            "recent_posts" => self.recent_posts(listings).await.map(|posts| {
+                let limit = setting_number(settings, "number", 5, MAX_RECENT_POSTS);
+                let links = posts.iter().take(limit);
+                link_list("widget-recent-posts", links.map(|(t, url)| (t, url, None)))
+            }),
+            "categories" => self.terms(CATEGORY_TAXONOMY, listings).await.map(|terms| {
+                let show_count = settings
+                    .get("show_count")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
+                let links = terms
+                    .iter()
+                    .map(|(name, url, count)| (name, url, show_count.then_some(*count)));
+                link_list("widget-categories", links)
+            }),
+            "tag_cloud" => self.terms(TAG_TAXONOMY, listings).await.map(|terms| {
+                let limit = setting_number(settings, "number", DEFAULT_CLOUD_SIZE, 100);
+                tag_cloud(terms, limit)
+            }),
+            "navigation" => self.navigation(theme_id, settings, listings).await,
+            block if block.contains('/') => Ok(render_block(renderer, widget)),
+            _ => Ok(content),
+        };
+        result.unwrap_or_else(|e| {
+            tracing::warn!(widget = %widget.id, "Failed to render widget: {}", e);
+            String::new()
+        })
+    }
+
+    async fn recent_posts<'a>(&self, listings: &'a mut Listings) -> Result<&'a [(String, String)]> {
+        if listings.recent_posts.is_none() {
+            let posts: Vec<(String, String, String)> = sqlx::query_as(
+                r#"
+                SELECT title, slug, post_type::text
+                FROM posts
+                WHERE status = 'published' AND post_type = 'post' AND deleted_at IS NULL
+                  AND site_id IS NOT DISTINCT FROM $2
+                ORDER BY published_at DESC NULLS LAST
+                LIMIT $1
+                "#,
+            )
+            .bind(MAX_RECENT_POSTS)
+            .bind(self.themes.site_id())
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to load recent posts", e))?;
+            listings.recent_posts = Some(
+                posts
+                    .into_iter()
+                    .map(|(title, slug, post_type)| (title, content_path(&post_type, &slug)))
+                    .collect(),
+            );
+        }
+        Ok(listings.recent_posts.as_deref().unwrap_or_default())
+    }
+
+    /// Terms of a taxonomy carried by published posts, by name
+    async fn terms<'a>(
+        &self,
+        taxonomy: &'static str,
+        listings: &'a mut Listings,
+    ) -> Result<&'a [(String, String, i32)]> {
+        if !listings.terms.contains_key(taxonomy) {
+            let terms = match Taxonomy::load(&self.pool, taxonomy).await? {
+                Some(tx) if tx.public => {
+                    let rows: Vec<(String, String, i32)> = sqlx::query_as(
+                        "SELECT name, slug, count FROM terms \
+                         WHERE taxonomy_id = $1 AND count > 0 ORDER BY name",
+                    )
+                    .bind(tx.id)
+                    .fetch_all(&self.pool)
+                    .await
+                    .map_err(|e| Error::database_with_source("Failed to load terms", e))?;
+                    rows.into_iter()
+                        .map(|(name, slug, count)| (name, tx.term_path(&slug), count))
+                        .collect()
+                }
+                _ => Vec::new(),
+            };
+            listings.terms.insert(taxonomy, terms);
+        }
+        Ok(listings
+            .terms
+            .get(taxonomy)
+            .map(Vec::as_slice)
+            .unwrap_or_default())
+    }
+
+    /// Menu named by the widget's `menu` slug or `location`
+    async fn navigation(
+        &self,
+        theme_id: &str,
+        settings: &serde_json::Value,
+        listings: &mut Listings,
+    ) -> Result<String> {
+        if listings.menus.is_none() {
+            listings.menus = Some(self.menus.theme_menus(theme_id, None).await?);
+        }
+        let key = ["menu", "location"]
+            .iter()
+            .find_map(|k| settings.get(*k).and_then(|v| v.as_str()));
+        let menu = key.and_then(|k| listings.menus.as_ref().and_then(|m| m.get(k)));
+        Ok(match menu {
+            Some(menu) => format!(
+                r#"<nav class="widget-navigation">{}</nav>"#,
+                menu_list(&menu.items)
+            ),
+            None => String::new(),
+        })
+    }
+
+    /// Areas the theme declares, or the default ones when it declares none
+    async fn declared_areas(&self, theme_id: &str) -> Vec<RegisterArea> {
+        let declared = match self.themes.get_theme(theme_id).await {
+            Ok(Some(theme)) => parse_areas(&theme.widget_areas),
+            Ok(None) => Vec::new(),
+            Err(e) => {
+                tracing::warn!(theme_id, "Failed to load theme widget areas: {}", e);
+                Vec::new()
+            }
+        };
+        if declared.is_empty() {
+            DEFAULT_AREAS
+                .iter()
+                .map(|(slug, name)| RegisterArea {
+                    slug: slug.to_string(),
+                    name: name.to_string(),
+                    description: None,
+                    before_widget: None,
+                    after_widget: None,
+                    before_title: None,
+                    after_title: None,
+                })
+                .collect()
+        } else {
+            declared
+        }
+    }
+}
+
+/// Widget areas of a manifest: `{slug: name}`, `{slug: {name, ...}}` or
+/// `[{id, name, ...}]`. Later duplicates of a slug are dropped.
+fn parse_areas(value: &serde_json::Value) -> Vec<RegisterArea> {
+    let entries: Vec<(String, &serde_json::Value)> = match value {
+        serde_json::Value::Object(areas) => areas
+            .iter()
+            .map(|(slug, area)| (slug.clone(), area))
+            .collect(),
+        serde_json::Value::Array(areas) => areas
+            .iter()
+            .filter_map(|area| {
+                let slug = area.get("id").or_else(|| area.get("slug"))?.as_str()?;
+                Some((slug.to_string(), area))
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    let mut areas: Vec<RegisterArea> = Vec::new();
+    for (slug, area) in entries {
+        let slug = slug.trim().to_lowercase();
+        if !is_area_slug(&slug) || areas.iter().any(|a| a.slug == slug) {
+            continue;
+        }
+        let text = |key: &str| {
+            area.get(key)
+                .and_then(|v| v.as_str())
+                .map(str::to_string)
+                .filter(|v| !v.is_empty())
+        };
+        let name = area
+            .as_str()
+            .map(str::to_string)
+            .or_else(|| text("name"))
+            .unwrap_or_else(|| slug.clone());
+        areas.push(RegisterArea {
+            name,
+            description: text("description"),
+            before_widget: text("before_widget"),
+            after_widget: text("after_widget"),
+            before_title: text("before_title"),
+            after_title: text("after_title"),
+            slug,
+        });
+    }
+    areas
+}
+
+fn register_area(row: &WidgetAreaRow) -> RegisterArea {
+    RegisterArea {
+        slug: row.slug.clone(),
+        name: row.name.clone(),
+        description: row.description.clone(),
+        before_widget: row.before_widget.clone(),
+        after_widget: row.after_widget.clone(),
+        before_title: row.before_title.clone(),
+        after_title: row.after_title.clone(),
+    }
+}
+
+fn group_by_area(widgets: Vec<WidgetRow>) -> HashMap<Uuid, Vec<WidgetRow>> {
+    let mut grouped: HashMap<Uuid, Vec<WidgetRow>> = HashMap::new();
+    for widget in widgets {
+        if let Some(area_id) = widget.area_id {
+            grouped.entry(area_id).or_default().push(widget);
+        }
+    }
+    grouped
+}
+
+/// Classic widget type, with older names mapped, or a `namespace/name`
+/// block name
+fn widget_type(value: &str) -> Result<String> {
+    let value = value.trim();
+    if let Some((_, current)) = WIDGET_ALIASES.iter().find(|(alias, _)| *alias == value) {
+        return Ok(current.to_string());
+    }
+    if CLASSIC_WIDGETS.iter().any(|(t, _, _)| *t == value) {
+        return Ok(value.to_string());
+    }
+    let is_name = |part: &str| {
+        !part.is_empty()
+            && part
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+    };
+    match value.split_once('/') {
+        Some((namespace, name)) if value.len() <= 100 && is_name(namespace) && is_name(name) => {
+            Ok(value.to_string())
+        }
+        _ => Err(Error::invalid_input(
+            "widget_type",
+            "Widgets are classic widgets or blocks named like core/paragraph",
+        )),
+    }
+}
+
+/// Render a block widget: its settings are the block attributes and its
+/// content the inner blocks as JSON, or else the inner HTML
+fn render_block(renderer: &BlockRenderer, widget: &WidgetRow) -> String {
+    let content = widget.content.clone().unwrap_or_default();
+    let mut block = Block::new(&widget.widget_type);
+    block.id = widget.id.to_string();
+    block.attributes = widget.settings.clone();
+    match BlockParser::parse(&content) {
+        Ok(inner) if content.trim_start().starts_with('[') => block.inner_blocks = inner,
+        _ => block.inner_html = content,
+    }
+    renderer.render_block(&block)
+}
+
+/// Area markup: each widget in the area's wrappers, or the default ones
+fn area_html(area: &WidgetAreaRow, widgets: &[WidgetData]) -> String {
+    let wrapper = |value: &Option<String>, default: &'static str| {
+        value.clone().unwrap_or_else(|| default.to_string())
+    };
+    let before_widget = wrapper(&area.before_widget, DEFAULT_BEFORE_WIDGET);
+    let after_widget = wrapper(&area.after_widget, DEFAULT_AFTER_WIDGET);
+    let before_title = wrapper(&area.before_title, DEFAULT_BEFORE_TITLE);
+    let after_title = wrapper(&area.after_title, DEFAULT_AFTER_TITLE);
+
+    let mut html = String::new();
+    for widget in widgets {
+        let class = format!("widget-{}", widget.widget_type.replace(['/', '_'], "-"));
+        html.push_str(
+            &before_widget
+                .replace("{id}", &format!("widget-{}", widget.id))
+                .replace("{class}", &class),
+        );
+        if let Some(ref title) = widget.title {
+            html.push_str(&before_title);
+            html.push_str(&escape_html(title));
+            html.push_str(&after_title);
+        }
+        html.push_str(&widget.content);
+        html.push_str(&after_widget);
+    }
+    html
+}
+
+fn search_form(settings: &serde_json::Value) -> String {
+    let placeholder = settings
+        .get("placeholder")
+        .and_then(|v| v.as_str())
+        .unwrap_or("Search...");
+    format!(
+        r#"<form role="search" method="get" action="/search" class="widget-search"><input type="search" name="q" placeholder="{}" class="search-input"><button type="submit" class="search-button">Search</button></form>"#,
+        escape_html(placeholder)
+    )
+}
+
+/// Links as a list, each optionally followed by a count
+fn link_list<'a>(
+    class: &str,
+    links: impl Iterator<Item = (&'a String, &'a String, Option<i32>)>,
+) -> String {
+    let mut html = format!(r#"<ul class="{}">"#, class);
+    for (title, url, count) in links {
+        html.push_str(&format!(
+            r#"<li><a href="{}">{}</a>"#,
+            escape_html(url),
+            escape_html(title)
+        ));
+        if let Some(count) = count {
+            html.push_str(&format!(" ({})", count));
+        }
+        html.push_str("</li>");
+    }
+    html.push_str("</ul>");
+    html
+}
+
+/// The most used terms by name, sized by use from 0.8em to 1.6em
+fn tag_cloud(terms: &[(String, String, i32)], limit: usize) -> String {
+    let mut top: Vec<&(String, String, i32)> = terms.iter().collect();
+    top.sort_by(|a, b| b.2.cmp(&a.2).then_with(|| a.0.cmp(&b.0)));
+    top.truncate(limit);
+    top.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let min = top.iter().map(|t| t.2).min().unwrap_or(0);
+    let max = top.iter().map(|t| t.2).max().unwrap_or(0);
+    let mut html = String::from(r#"<div class="widget-tag-cloud">"#);
+    for (name, url, count) in top {
+        let weight = if max > min {
+            (count - min) as f64 / (max - min) as f64
+        } else {
+            0.5
+        };
+        html.push_str(&format!(
+            r#"<a href="{}" style="font-size: {:.2}em">{}</a> "#,
+            escape_html(url),
+            0.8 + 0.8 * weight,
+            escape_html(name)
+        ));
+    }
+    html.push_str("</div>");
+    html
+}
+
+fn menu_list(items: &[MenuItemData]) -> String {
+    if items.is_empty() {
+        return String::new();
+    }
+    let mut html = String::from("<ul>");
+    for item in items {
+        let target = item
+            .target
+            .as_deref()
+            .map(|t| format!(r#" target="{}""#, escape_html(t)))
+            .unwrap_or_default();
+        html.push_str(&format!(
+            r#"<li><a href="{}"{}>{}</a>{}</li>"#,
+            escape_html(&item.url),
+            target,
+            escape_html(&item.title),
+            menu_list(&item.children)
+        ));
+    }
+    html.push_str("</ul>");
+    html
+}
+
+fn setting_number(settings: &serde_json::Value, key: &str, default: i64, max: i64) -> usize {
+    settings
+        .get(key)
+        .and_then(|v| v.as_i64())
+        .unwrap_or(default)
+        .clamp(1, max) as usize
+}
+
+/// Lowercase letters, digits, `-` and `_`
+fn is_area_slug(value: &str) -> bool {
+    !value.is_empty()
+        && value.len() <= 100
+        && value
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn area(before_widget: Option<&str>) -> WidgetAreaRow {
+        WidgetAreaRow {
+            id: Uuid::from_u128(1),
+            theme: "theme".to_string(),
+            slug: "sidebar".to_string(),
+            name: "Sidebar".to_string(),
+            description: None,
+            before_widget: before_widget.map(str::to_string),
+            after_widget: before_widget.map(|_| "</div>".to_string()),
+            before_title: None,
+            after_title: None,
+            position: 0,
+        }
+    }
+
+    fn widget(widget_type: &str, title: Option<&str>, content: &str) -> WidgetData {
+        WidgetData {
+            id: "w1".to_string(),
+            widget_type: widget_type.to_string(),
+            title: title.map(str::to_string),
+            content: content.to_string(),
+            settings: serde_json::json!({}),
+        }
+    }
+
+    #[test]
+    fn test_parse_areas() {
+        let areas = parse_areas(&serde_json::json!({
+            "blog": {"name": "Blog Sidebar", "description": "Blog pages"},
+            "footer": "Footer"
+        }));
+        assert_eq!(areas.len(), 2);
+        assert_eq!(areas[0].slug, "blog");
+        assert_eq!(areas[0].name, "Blog Sidebar");
+        assert_eq!(areas[0].description.as_deref(), Some("Blog pages"));
+        assert_eq!(areas[1].name, "Footer");
+
+        let areas = parse_areas(&serde_json::json!([
+            {"id": "Main", "name": "Main", "before_widget": "<div id=\"{id}\">"},
+            {"slug": "main", "name": "Duplicate"},
+            {"id": "bad slug"},
+            {"name": "No slug"}
+        ]));
+        assert_eq!(areas.len(), 1);
+        assert_eq!(areas[0].slug, "main");
+        assert_eq!(areas[0].before_widget.as_deref(), Some("<div id=\"{id}\">"));
+
+        assert!(parse_areas(&serde_json::json!(null)).is_empty());
+    }
+
+    #[test]
+    fn test_widget_types() {
+        assert_eq!(widget_type("search").unwrap(), "search");
+        assert_eq!(widget_type("tags").unwrap(), "tag_cloud");
+        assert_eq!(widget_type("custom_html").unwrap(), "html");
+        assert_eq!(widget_type("core/paragraph").unwrap(), "core/paragraph");
+        assert_eq!(
+            widget_type("my-plugin/latest-2").unwrap(),
+            "my-plugin/latest-2"
+        );
+        assert!(widget_type("unknown").is_err());
+        assert!(widget_type("core/").is_err());
+        assert!(widget_type("Core/Paragraph").is_err());
+    }
+
+    #[test]
+    fn test_input_validation() {
+        let input = |settings: serde_json::Value, title: &str| WidgetInput {
+            area: Some("sidebar".to_string()),
+            theme: None,
+            widget_type: "text".to_string(),
+            title: Some(title.to_string()),
+            content: Some("Hello".to_string()),
+            settings,
+            is_active: true,
+        };
+
+        let widget = input(serde_json::Value::Null, "  ")
+            .normalize(Uuid::from_u128(1))
+            .unwrap();
+        assert_eq!(widget.settings, serde_json::json!({}));
+        assert_eq!(widget.title, None);
+
+        assert!(input(serde_json::json!([1]), "Title")
+            .normalize(Uuid::from_u128(1))
+            .is_err());
+        assert!(input(serde_json::json!({}), &"x".repeat(256))
+            .normalize(Uuid::from_u128(1))
+            .is_err());
+    }
+
+    #[test]
+    fn test_area_html() {
+        let widgets = [widget("recent_posts", Some("<News>"), "<ul></ul>")];
+        assert_eq!(
+            area_html(&area(None), &widgets),
+            "<section id=\"widget-w1\" class=\"widget widget-recent-posts\">\
+             <h2 class=\"widget-title\">&lt;News&gt;</h2><ul></ul></section>"
+        );
+
+        let widgets = [widget("core/paragraph", None, "<p>Hi</p>")];
+        assert_eq!(
+            area_html(&area(Some("<div id=\"{id}\" class=\"{class}\">")), &widgets),
+            "<div id=\"widget-w1\" class=\"widget-core-paragraph\"><p>Hi</p></div>"
+        );
+    }
+
+    #[test]
+    fn test_render_block() {
+        let renderer = BlockRenderer::new();
+        let mut row = WidgetRow {
+            id: Uuid::from_u128(1),
+            area_id: None,
+            widget_type: "core/paragraph".to_string(),
+            title: None,
+            content: None,
+            settings: serde_json::json!({"content": "Hello"}),
+            widget_order: 0,
+            is_active: true,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        };
+        assert_eq!(render_block(&renderer, &row), "<p>Hello</p>\n");
+
+        row.widget_type = "core/group".to_string();
+        row.content = Some(serde_json::to_string(&[Block::paragraph("Inner")]).unwrap());
+        assert!(render_block(&renderer, &row).contains("<p>Inner</p>"));
+
+        row.widget_type = "acme/banner".to_string();
+        row.content = Some("<div>Banner</div>".to_string());
+        assert_eq!(render_block(&renderer, &row), "<div>Banner</div>");
+    }
+
+    #[test]
+    fn test_tag_cloud() {
+        let terms = vec![
+            ("rust".to_string(), "/tag/rust".to_string(), 10),
+            ("go".to_string(), "/tag/go".to_string(), 1),
+            ("zig".to_string(), "/tag/zig".to_string(), 5),
+        ];
+        let html = tag_cloud(&terms, 2);
+        assert!(!html.contains("/tag/go"));
+        let rust = html.find("1.60em\">rust").unwrap();
+        let zig = html.find("0.80em\">zig").unwrap();
+        assert!(rust < zig);
+    }
+}
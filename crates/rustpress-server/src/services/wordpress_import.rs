@@ -0,0 +1,1248 @@
+//! WordPress Import
+//!
+//! Imports a WordPress export file (WXR) into the site. An import is
+//! started as a run whose items are processed in the background, with
+//! counters updated as they go so the admin UI and `rustpress import
+//! wordpress` can report progress.
+//!
+//! - authors are matched to existing users by email, then login, and
+//!   created as authors otherwise (or everything is attributed to the
+//!   importing user)
+//! - categories and tags are matched by slug and created when missing
+//! - attachments are downloaded and stored through [`Storage`]; references
+//!   to their old URLs, and links between imported posts, are rewritten
+//! - posts whose slug already exists are skipped, so an interrupted import
+//!   can simply be run again
+//! - every imported post, page and attachment gets a redirect from its old
+//!   permalink, answered by the router's fallback
+//!   ([`RedirectService`](super::RedirectService))
+
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use rustpress_api::services::media_service::{MediaService, UploadMediaMetadata};
+use rustpress_api::services::user_service::{CreateUserRequest, UserService};
+use rustpress_content::{WxrAuthor, WxrComment, WxrDocument, WxrItem, WxrParser};
+use rustpress_core::error::{Error, Result};
+use rustpress_core::http::HttpClient;
+use rustpress_core::tenant;
+use rustpress_storage::Storage;
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use sqlx::{FromRow, PgPool, Postgres, Transaction};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use uuid::Uuid;
+
+use super::redirects::{content_path, NewRedirect, RedirectService};
+use super::user_import::generate_password;
+
+/// Endpoint WXR files are uploaded to, which allows larger bodies than
+/// the rest of the API
+pub const IMPORT_PATH: &str = "/api/v1/import/wordpress";
+
+/// Largest WXR file accepted
+pub const MAX_WXR_SIZE: usize = 64 * 1024 * 1024;
+
+/// Largest attachment downloaded
+const MAX_MEDIA_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Redirects followed for an attachment, each checked like the first URL
+const MAX_MEDIA_REDIRECTS: usize = 5;
+
+/// Item failures kept on a run; later ones are only counted
+const MAX_REPORTED_ERRORS: usize = 100;
+
+/// Items processed between progress updates
+const PROGRESS_INTERVAL: usize = 10;
+
+/// Storage directory of downloaded attachments
+const MEDIA_DIRECTORY: &str = "imports/wordpress";
+
+/// `origin` of the redirects an import creates
+pub const REDIRECT_ORIGIN: &str = "wordpress-import";
+
+fn default_true() -> bool {
+    true
+}
+
+/// What to import
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WordpressImportOptions {
+    /// Download attachments into storage
+    #[serde(default = "default_true")]
+    pub media: bool,
+    #[serde(default = "default_true")]
+    pub comments: bool,
+    /// Match or create the file's authors; otherwise everything is
+    /// attributed to the importing user
+    #[serde(default = "default_true")]
+    pub authors: bool,
+    /// Redirect old permalinks to the imported content
+    #[serde(default = "default_true")]
+    pub redirects: bool,
+    /// Count what would be imported without writing anything
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+impl Default for WordpressImportOptions {
+    fn default() -> Self {
+        Self {
+            media: true,
+            comments: true,
+            authors: true,
+            redirects: true,
+            dry_run: false,
+        }
+    }
+}
+
+/// An item that could not be imported
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportItemError {
+    /// WordPress post ID
+    pub item: i64,
+    pub title: String,
+    pub message: String,
+}
+
+/// A WordPress import run and its progress
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct WordpressImportRun {
+    pub id: Uuid,
+    pub started_by: Option<Uuid>,
+    /// `running`, `completed` or `failed`
+    pub status: String,
+    pub dry_run: bool,
+    /// Site the file was exported from
+    pub source_url: Option<String>,
+    /// Posts, pages and attachments in the file
+    pub total: i32,
+    pub processed: i32,
+    pub authors: i32,
+    pub categories: i32,
+    pub tags: i32,
+    pub posts: i32,
+    pub pages: i32,
+    pub media: i32,
+    pub comments: i32,
+    pub redirects: i32,
+    pub skipped: i32,
+    pub failed: i32,
+    pub errors: Json<Vec<ImportItemError>>,
+    pub created_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+const RUN_COLUMNS: &str = "id, started_by, status, dry_run, source_url, total, processed, \
+     authors, categories, tags, posts, pages, media, comments, redirects, skipped, failed, \
+     errors, created_at, finished_at";
+
+/// Post status for a WordPress status; trashed and automatic drafts are
+/// not imported
+pub fn map_post_status(status: &str) -> Option<&'static str> {
+    match status {
+        "publish" => Some("published"),
+        "future" => Some("scheduled"),
+        "draft" => Some("draft"),
+        "pending" => Some("pending"),
+        "private" => Some("private"),
+        _ => None,
+    }
+}
+
+/// Comment status for WordPress' `comment_approved`
+pub fn map_comment_status(approved: &str) -> Option<&'static str> {
+    match approved {
+        "1" => Some("approved"),
+        "0" => Some("pending"),
+        "spam" => Some("spam"),
+        _ => None,
+    }
+}
+
+/// Whether an item is a post or page this import handles
+fn is_content(item: &WxrItem) -> bool {
+    matches!(item.post_type.as_str(), "post" | "page")
+}
+
+/// Slug of an imported post: its WordPress slug, or one made from the title
+fn item_slug(item: &WxrItem) -> String {
+    let slug = if item.post_name.trim().is_empty() {
+        slugify::slugify(&item.title, "", "-", Some(200))
+    } else {
+        decode_slug(&item.post_name)
+    };
+    if slug.is_empty() {
+        format!("{}-{}", item.post_type, item.post_id)
+    } else {
+        slug
+    }
+}
+
+/// WordPress percent-encodes non-ASCII slugs
+fn decode_slug(slug: &str) -> String {
+    let slug = slug.trim();
+    urlencoding::decode(slug)
+        .map(|s| s.into_owned())
+        .unwrap_or_else(|_| slug.to_string())
+}
+
+/// Path of an old WordPress URL, for redirects
+fn link_path(link: &str) -> Option<String> {
+    let path = match reqwest::Url::parse(link) {
+        Ok(url) => url.path().to_string(),
+        Err(_) => link.to_string(),
+    };
+    super::redirects::normalize_path(&path)
+}
+
+/// Key URLs are compared by: no scheme, no trailing slash
+fn url_key(url: &str) -> String {
+    let url = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+        .or_else(|| url.strip_prefix("//"))
+        .unwrap_or(url);
+    url.trim_end_matches('/').to_string()
+}
+
+/// Replace links to old URLs in content with their new location
+fn rewrite_urls(content: &str, urls: &HashMap<String, String>) -> String {
+    static URL: OnceLock<Regex> = OnceLock::new();
+    if urls.is_empty() {
+        return content.to_string();
+    }
+    let pattern = URL.get_or_init(|| Regex::new(r#"https?://[^\s"'<>()\[\]]+"#).unwrap());
+    pattern
+        .replace_all(content, |caps: &regex::Captures| {
+            let found = &caps[0];
+            urls.get(&url_key(found))
+                .cloned()
+                .unwrap_or_else(|| found.to_string())
+        })
+        .into_owned()
+}
+
+/// Decode the entities WordPress leaves in term names
+fn decode_entities(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#039;", "'")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+pub(crate) fn truncate(text: &str, max: usize) -> String {
+    text.chars().take(max).collect()
+}
+
+/// MIME type of a download, falling back to the file extension
+pub(crate) fn media_type(content_type: Option<&str>, filename: &str) -> String {
+    let declared = content_type
+        .and_then(|ct| ct.split(';').next())
+        .map(|ct| ct.trim().to_lowercase())
+        .filter(|ct| !ct.is_empty() && ct != "application/octet-stream");
+    if let Some(declared) = declared {
+        return declared;
+    }
+    let ext = filename.rsplit('.').next().unwrap_or("").to_lowercase();
+    match ext.as_str() {
+        "jpg" | "jpeg" => "image/jpeg",
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "pdf" => "application/pdf",
+        "mp3" => "audio/mpeg",
+        "mp4" => "video/mp4",
+        "zip" => "application/zip",
+        _ => "application/octet-stream",
+    }
+    .to_string()
+}
+
+/// Refuse attachment URLs that point into the server's own network;
+/// returns the addresses checked, for the download to connect to
+async fn check_download_url(url: &str) -> Result<(reqwest::Url, Vec<SocketAddr>)> {
+    let url = reqwest::Url::parse(url)
+        .map_err(|_| Error::invalid_input("attachment_url", "Invalid attachment URL"))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(Error::invalid_input(
+            "attachment_url",
+            "Attachments must be downloaded over HTTP(S)",
+        ));
+    }
+    let host = url
+        .host_str()
+        .ok_or_else(|| Error::invalid_input("attachment_url", "Attachment URL has no host"))?;
+    let port = url.port_or_known_default().unwrap_or(80);
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.trim_matches(['[', ']']), port))
+        .await
+        .map_err(|e| Error::internal(format!("Failed to resolve {}: {}", host, e)))?
+        .collect();
+    if addrs.is_empty() || addrs.iter().any(|addr| !is_public_ip(addr.ip())) {
+        return Err(Error::invalid_input(
+            "attachment_url",
+            format!("Attachment host {} is not a public address", host),
+        ));
+    }
+    Ok((url, addrs))
+}
+
+/// Whether an address lies outside the server's own network
+pub(crate) fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.octets()[0] == 100 && (ip.octets()[1] & 0xc0) == 64)
+        }
+        IpAddr::V6(ip) => {
+            let first = ip.segments()[0];
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || (first & 0xfe00) == 0xfc00
+                || (first & 0xffc0) == 0xfe80
+                || ip
+                    .to_ipv4_mapped()
+                    .is_some_and(|v4| !is_public_ip(IpAddr::V4(v4))))
+        }
+    }
+}
+
+/// Counters of a run while it is processed
+#[derive(Debug, Default)]
+struct ImportProgress {
+    processed: i32,
+    authors: i32,
+    categories: i32,
+    tags: i32,
+    posts: i32,
+    pages: i32,
+    media: i32,
+    comments: i32,
+    redirects: i32,
+    skipped: i32,
+    failed: i32,
+    errors: Vec<ImportItemError>,
+}
+
+impl ImportProgress {
+    fn fail(&mut self, item: &WxrItem, message: String) {
+        self.failed += 1;
+        if self.errors.len() < MAX_REPORTED_ERRORS {
+            self.errors.push(ImportItemError {
+                item: item.post_id,
+                title: item.title.clone(),
+                message,
+            });
+        }
+    }
+}
+
+/// State shared by the items of one run
+struct ImportContext {
+    run_id: Uuid,
+    options: WordpressImportOptions,
+    started_by: Uuid,
+    /// Users by WordPress login
+    authors: HashMap<String, Uuid>,
+    /// Users by WordPress user ID, for comments by registered users
+    author_ids: HashMap<i64, Uuid>,
+    categories: HashMap<String, Uuid>,
+    tags: HashMap<String, Uuid>,
+    /// Media by WordPress attachment ID
+    media: HashMap<i64, Uuid>,
+    /// New locations of old URLs, by [`url_key`]
+    urls: HashMap<String, String>,
+    redirects: Vec<NewRedirect>,
+    progress: ImportProgress,
+}
+
+/// WordPress imports
+pub struct WordpressImportService {
+    pool: PgPool,
+    storage: Arc<Storage>,
+    http: HttpClient,
+    redirects: RedirectService,
+}
+
+impl WordpressImportService {
+    pub fn new(pool: PgPool, storage: Arc<Storage>, http: HttpClient) -> Self {
+        Self {
+            redirects: RedirectService::new(pool.clone()),
+            pool,
+            storage,
+            http,
+        }
+    }
+
+    /// Parse a WXR file and start importing it in the background
+    pub async fn start(
+        self: &Arc<Self>,
+        started_by: Uuid,
+        options: WordpressImportOptions,
+        xml: String,
+    ) -> Result<WordpressImportRun> {
+        if xml.len() > MAX_WXR_SIZE {
+            return Err(Error::invalid_input(
+                "file",
+                format!("WXR files can be at most {} bytes", MAX_WXR_SIZE),
+            ));
+        }
+        let doc = tokio::task::spawn_blocking(move || WxrParser::parse(&xml))
+            .await
+            .map_err(|e| Error::internal(format!("WXR parsing failed: {}", e)))?
+            .map_err(|e| Error::invalid_input("file", format!("Invalid WXR file: {}", e)))?;
+        if doc.version.is_empty() && doc.items.is_empty() {
+            return Err(Error::invalid_input(
+                "file",
+                "Not a WordPress export (no wp:wxr_version or items)",
+            ));
+        }
+
+        let total = doc
+            .items
+            .iter()
+            .filter(|item| is_content(item) || item.post_type == "attachment")
+            .count();
+        let source_url = Some(doc.site.base_site_url.clone())
+            .filter(|url| !url.is_empty())
+            .or_else(|| Some(doc.site.link.clone()).filter(|url| !url.is_empty()));
+
+        let run: WordpressImportRun = sqlx::query_as(&format!(
+            "INSERT INTO wordpress_imports (id, started_by, dry_run, source_url, total) \
+             VALUES ($1, $2, $3, $4, $5) RETURNING {}",
+            RUN_COLUMNS
+        ))
+        .bind(Uuid::now_v7())
+        .bind(started_by)
+        .bind(options.dry_run)
+        .bind(source_url.map(|url| truncate(&url, 500)))
+        .bind(total as i32)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to start WordPress import", e))?;
+
+        let service = self.clone();
+        let run_id = run.id;
+        // The import runs on the site that started it
+        tenant::spawn(async move {
+            let worker = service.clone();
+            let task =
+                tenant::spawn(
+                    async move { worker.process(run_id, started_by, doc, options).await },
+                );
+            // Don't leave the run looking busy forever when processing dies
+            if task.await.is_err() {
+                tracing::error!(import_id = %run_id, "WordPress import aborted");
+                let result = sqlx::query(
+                    "UPDATE wordpress_imports SET status = 'failed', finished_at = NOW() \
+                     WHERE id = $1",
+                )
+                .bind(run_id)
+                .execute(&service.pool)
+                .await;
+                if let Err(e) = result {
+                    tracing::error!(import_id = %run_id, "Failed to mark WordPress import failed: {}", e);
+                }
+            }
+        });
+        Ok(run)
+    }
+
+    /// An import run
+    pub async fn get(&self, id: Uuid) -> Result<WordpressImportRun> {
+        sqlx::query_as(&format!(
+            "SELECT {} FROM wordpress_imports WHERE id = $1",
+            RUN_COLUMNS
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to load WordPress import", e))?
+        .ok_or_else(|| Error::not_found("WordPress import", id.to_string()))
+    }
+
+    /// Most recent import runs
+    pub async fn list(&self, limit: i64) -> Result<Vec<WordpressImportRun>> {
+        sqlx::query_as(&format!(
+            "SELECT {} FROM wordpress_imports ORDER BY created_at DESC LIMIT $1",
+            RUN_COLUMNS
+        ))
+        .bind(limit.clamp(1, 100))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to list WordPress imports", e))
+    }
+
+    /// Redirect map of an import, as `old path,new location` CSV lines
+    pub async fn redirect_map(&self, id: Uuid) -> Result<String> {
+        self.get(id).await?;
+        let mut csv = String::from("source,target\n");
+        for redirect in self.redirects.for_import(id).await? {
+            csv.push_str(&format!(
+                "{},{}\n",
+                csv_field(&redirect.source_path),
+                csv_field(&redirect.target)
+            ));
+        }
+        Ok(csv)
+    }
+
+    async fn process(
+        &self,
+        run_id: Uuid,
+        started_by: Uuid,
+        doc: WxrDocument,
+        options: WordpressImportOptions,
+    ) {
+        let mut ctx = ImportContext {
+            run_id,
+            options,
+            started_by,
+            authors: HashMap::new(),
+            author_ids: HashMap::new(),
+            categories: HashMap::new(),
+            tags: HashMap::new(),
+            media: HashMap::new(),
+            urls: HashMap::new(),
+            redirects: Vec::new(),
+            progress: ImportProgress::default(),
+        };
+
+        if ctx.options.authors {
+            for author in &doc.authors {
+                if let Err(e) = self.import_author(&mut ctx, author).await {
+                    tracing::warn!(import_id = %run_id, login = %author.login, "Author not imported: {}", e);
+                }
+            }
+        }
+        for category in &doc.categories {
+            let name = decode_entities(&category.name);
+            let result = self
+                .ensure_term(
+                    &mut ctx,
+                    "category",
+                    &category.nicename,
+                    &name,
+                    &category.description,
+                )
+                .await;
+            if let Err(e) = result {
+                tracing::warn!(import_id = %run_id, slug = %category.nicename, "Category not imported: {}", e);
+            }
+        }
+        if !ctx.options.dry_run {
+            self.link_category_parents(&doc, &ctx.categories).await;
+        }
+        for tag in &doc.tags {
+            let name = decode_entities(&tag.name);
+            if let Err(e) = self
+                .ensure_term(&mut ctx, "post_tag", &tag.slug, &name, &tag.description)
+                .await
+            {
+                tracing::warn!(import_id = %run_id, slug = %tag.slug, "Tag not imported: {}", e);
+            }
+        }
+
+        // Attachments go first so posts can point at their new URLs
+        let (attachments, content): (Vec<&WxrItem>, Vec<&WxrItem>) = doc
+            .items
+            .iter()
+            .filter(|item| is_content(item) || item.post_type == "attachment")
+            .partition(|item| item.post_type == "attachment");
+
+        for item in &content {
+            if map_post_status(&item.status).is_some() {
+                let path = content_path(&item.post_type, &item_slug(item));
+                ctx.urls.insert(url_key(&item.link), path);
+            }
+        }
+
+        for (index, item) in attachments.iter().chain(content.iter()).enumerate() {
+            let result = if item.post_type == "attachment" {
+                self.import_attachment(&mut ctx, item).await
+            } else {
+                self.import_content(&mut ctx, item).await
+            };
+            if let Err(e) = result {
+                ctx.progress.fail(item, e.to_string());
+            }
+            ctx.progress.processed += 1;
+
+            if (index + 1) % PROGRESS_INTERVAL == 0 {
+                self.save_progress(run_id, &ctx.progress, None).await;
+            }
+        }
+
+        if ctx.options.redirects {
+            if ctx.options.dry_run {
+                ctx.progress.redirects = ctx.redirects.len() as i32;
+            } else {
+                match self
+                    .redirects
+                    .save(&ctx.redirects, REDIRECT_ORIGIN, Some(run_id))
+                    .await
+                {
+                    Ok(saved) => ctx.progress.redirects = saved as i32,
+                    Err(e) => {
+                        tracing::error!(import_id = %run_id, "Failed to save redirects: {}", e)
+                    }
+                }
+            }
+        }
+
+        tracing::info!(
+            import_id = %run_id,
+            posts = ctx.progress.posts,
+            pages = ctx.progress.pages,
+            media = ctx.progress.media,
+            comments = ctx.progress.comments,
+            failed = ctx.progress.failed,
+            dry_run = ctx.options.dry_run,
+            "WordPress import finished"
+        );
+        self.save_progress(run_id, &ctx.progress, Some("completed"))
+            .await;
+    }
+
+    /// Match an author to a user, creating one when needed
+    async fn import_author(&self, ctx: &mut ImportContext, author: &WxrAuthor) -> Result<()> {
+        let users = UserService::new(self.pool.clone());
+        let email = author.email.trim().to_lowercase();
+
+        let mut user_id = None;
+        if !email.is_empty() {
+            user_id = users.get_user_by_email(&email).await?.map(|u| u.id);
+        }
+        if user_id.is_none() && !author.login.is_empty() {
+            user_id = users
+                .get_user_by_username(&author.login)
+                .await?
+                .map(|u| u.id);
+        }
+        let user_id = match user_id {
+            Some(id) => id,
+            None if email.is_empty() => return Ok(()),
+            None if ctx.options.dry_run => {
+                ctx.progress.authors += 1;
+                return Ok(());
+            }
+            None => {
+                let display_name = Some(author.display_name.trim())
+                    .filter(|name| !name.is_empty())
+                    .map(decode_entities);
+                let created = users
+                    .create_user(CreateUserRequest {
+                        email,
+                        username: author.login.clone(),
+                        password: generate_password(),
+                        display_name,
+                        role: Some("author".to_string()),
+                        locale: None,
+                        timezone: None,
+                    })
+                    .await?;
+                ctx.progress.authors += 1;
+                created.id
+            }
+        };
+
+        ctx.authors.insert(author.login.clone(), user_id);
+        ctx.author_ids.insert(author.id, user_id);
+        Ok(())
+    }
+
+    /// ID of a category (`category`) or tag (`post_tag`), created when
+    /// missing
+    async fn ensure_term(
+        &self,
+        ctx: &mut ImportContext,
+        taxonomy: &str,
+        slug: &str,
+        name: &str,
+        description: &str,
+    ) -> Result<Option<Uuid>> {
+        let slug = match decode_slug(slug) {
+            slug if slug.is_empty() => slugify::slugify(name, "", "-", Some(200)),
+            slug => truncate(&slug, 200),
+        };
+        if slug.is_empty() {
+            return Ok(None);
+        }
+        let (table, cache) = match taxonomy {
+            "category" => ("categories", &mut ctx.categories),
+            _ => ("tags", &mut ctx.tags),
+        };
+        if let Some(id) = cache.get(&slug) {
+            return Ok(Some(*id));
+        }
+
+        let existing: Option<Uuid> =
+            sqlx::query_scalar(&format!("SELECT id FROM {} WHERE slug = $1", table))
+                .bind(&slug)
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(|e| Error::database_with_source("Failed to look up term", e))?;
+        let id = match existing {
+            Some(id) => id,
+            None => {
+                let id = if ctx.options.dry_run {
+                    Uuid::now_v7()
+                } else {
+                    let name = if name.trim().is_empty() { &slug } else { name };
+                    // Another request may have created it since the lookup
+                    sqlx::query_scalar(&format!(
+                        "INSERT INTO {} (id, name, slug, description) VALUES ($1, $2, $3, $4) \
+                         ON CONFLICT (slug) DO UPDATE SET slug = EXCLUDED.slug RETURNING id",
+                        table
+                    ))
+                    .bind(Uuid::now_v7())
+                    .bind(truncate(name, 255))
+                    .bind(&slug)
+                    .bind(Some(description).filter(|d| !d.is_empty()))
+                    .fetch_one(&self.pool)
+                    .await
+                    .map_err(|e| Error::database_with_source("Failed to create term", e))?
+                };
+                match taxonomy {
+                    "category" => ctx.progress.categories += 1,
+                    _ => ctx.progress.tags += 1,
+                }
+                id
+            }
+        };
+
+        cache.insert(slug, id);
+        Ok(Some(id))
+    }
+
+    /// Nest imported categories under their parents
+    async fn link_category_parents(&self, doc: &WxrDocument, categories: &HashMap<String, Uuid>) {
+        for category in doc.categories.iter().filter(|c| !c.parent.is_empty()) {
+            let (Some(id), Some(parent)) = (
+                categories.get(&category.nicename),
+                categories.get(&category.parent),
+            ) else {
+                continue;
+            };
+            let result = sqlx::query(
+                "UPDATE categories SET parent_id = $2 WHERE id = $1 AND parent_id IS NULL",
+            )
+            .bind(id)
+            .bind(parent)
+            .execute(&self.pool)
+            .await;
+            if let Err(e) = result {
+                tracing::warn!(slug = %category.nicename, "Failed to set category parent: {}", e);
+            }
+        }
+    }
+
+    /// Download an attachment into storage and add it to the media library
+    async fn import_attachment(&self, ctx: &mut ImportContext, item: &WxrItem) -> Result<()> {
+        let Some(old_url) = item.attachment_url.as_deref().filter(|url| !url.is_empty()) else {
+            ctx.progress.skipped += 1;
+            return Ok(());
+        };
+        if !ctx.options.media {
+            ctx.progress.skipped += 1;
+            return Ok(());
+        }
+        if ctx.options.dry_run {
+            ctx.progress.media += 1;
+            return Ok(());
+        }
+
+        let (url, mut addrs) = check_download_url(old_url).await?;
+        let filename = url
+            .path_segments()
+            .and_then(|mut segments| segments.next_back())
+            .map(|name| {
+                urlencoding::decode(name)
+                    .map(|n| n.into_owned())
+                    .unwrap_or_else(|_| name.to_string())
+            })
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| format!("attachment-{}", item.post_id));
+
+        // Redirects are followed here so every hop is checked, and each
+        // request connects to the addresses that were checked
+        let mut target = url;
+        let mut redirects = 0;
+        let mut response = loop {
+            let response = self
+                .http
+                .send_to(
+                    self.http
+                        .get(target.clone())
+                        .timeout(Duration::from_secs(60)),
+                    &addrs,
+                )
+                .await
+                .map_err(|e| Error::internal(format!("Download failed: {}", e)))?;
+            if !response.status().is_redirection() {
+                break response
+                    .error_for_status()
+                    .map_err(|e| Error::internal(format!("Download failed: {}", e)))?;
+            }
+            redirects += 1;
+            if redirects > MAX_MEDIA_REDIRECTS {
+                return Err(Error::internal("Download failed: too many redirects"));
+            }
+            let location = response
+                .headers()
+                .get(reqwest::header::LOCATION)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|location| target.join(location).ok())
+                .ok_or_else(|| Error::internal("Download failed: invalid redirect"))?;
+            (target, addrs) = check_download_url(location.as_str()).await?;
+        };
+        if response.content_length().unwrap_or(0) > MAX_MEDIA_SIZE {
+            return Err(Error::invalid_input("file", "Attachment is too large"));
+        }
+        let mime_type = media_type(
+            response
+                .headers()
+                .get(reqwest::header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok()),
+            &filename,
+        );
+        // The length header is optional, so enforce the limit while reading
+        let mut data = Vec::new();
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| Error::internal(format!("Download failed: {}", e)))?
+        {
+            if (data.len() + chunk.len()) as u64 > MAX_MEDIA_SIZE {
+                return Err(Error::invalid_input("file", "Attachment is too large"));
+            }
+            data.extend_from_slice(&chunk);
+        }
+        let data = Bytes::from(data);
+
+        let file_size = data.len() as i64;
+        let stored = self
+            .storage
+            .upload_to(data, &filename, &mime_type, MEDIA_DIRECTORY)
+            .await?;
+        let alt_text = item
+            .meta
+            .iter()
+            .find(|meta| meta.key == "_wp_attachment_image_alt")
+            .map(|meta| truncate(&meta.value, 500));
+        let uploaded_by = ctx
+            .authors
+            .get(&item.creator)
+            .copied()
+            .unwrap_or(ctx.started_by);
+        let metadata = UploadMediaMetadata {
+            alt_text,
+            title: Some(truncate(&item.title, 500)).filter(|t| !t.is_empty()),
+            description: Some(item.content.clone()).filter(|c| !c.is_empty()),
+            folder_id: None,
+        };
+
+        let media = MediaService::new(self.pool.clone())
+            .upload_media(
+                uploaded_by,
+                stored.filename.clone(),
+                truncate(&filename, 500),
+                mime_type,
+                file_size,
+                stored.path.clone(),
+                Some(metadata),
+                None,
+                None,
+            )
+            .await;
+        let media = match media {
+            Ok(media) => media,
+            Err(e) => {
+                let _ = self.storage.delete(&stored.path).await;
+                return Err(e);
+            }
+        };
+
+        let new_url = self
+            .storage
+            .url(&stored.path)
+            .unwrap_or_else(|| format!("/uploads/{}", stored.path));
+        ctx.media.insert(item.post_id, media.id);
+        ctx.urls.insert(url_key(old_url), new_url.clone());
+        if let Some(source) = link_path(old_url) {
+            ctx.redirects.push(NewRedirect {
+                source_path: source,
+                target: new_url.clone(),
+            });
+        }
+        if let Some(source) = link_path(&item.link) {
+            ctx.redirects.push(NewRedirect {
+                source_path: source,
+                target: new_url,
+            });
+        }
+        ctx.progress.media += 1;
+        Ok(())
+    }
+
+    /// Import a post or page with its terms and comments
+    async fn import_content(&self, ctx: &mut ImportContext, item: &WxrItem) -> Result<()> {
+        let Some(status) = map_post_status(&item.status) else {
+            ctx.progress.skipped += 1;
+            return Ok(());
+        };
+        let slug = truncate(&item_slug(item), 500);
+        let path = content_path(&item.post_type, &slug);
+        if let Some(source) = link_path(&item.link) {
+            ctx.redirects.push(NewRedirect {
+                source_path: source,
+                target: path.clone(),
+            });
+        }
+
+        let existing: Option<Uuid> = sqlx::query_scalar(&format!(
+            "SELECT id FROM posts WHERE slug = $1 AND {}",
+            tenant::site_filter("site_id")
+        ))
+        .bind(&slug)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to look up post", e))?;
+        if existing.is_some() {
+            ctx.progress.skipped += 1;
+            return Ok(());
+        }
+
+        let mut category_ids = Vec::new();
+        let mut tag_ids = Vec::new();
+        if item.post_type == "post" {
+            for term in &item.categories {
+                let taxonomy = match term.domain.as_str() {
+                    "category" => "category",
+                    "post_tag" => "post_tag",
+                    _ => continue,
+                };
+                let id = self
+                    .ensure_term(ctx, taxonomy, &term.nicename, &term.name, "")
+                    .await?;
+                match (taxonomy, id) {
+                    ("category", Some(id)) => category_ids.push(id),
+                    (_, Some(id)) => tag_ids.push(id),
+                    _ => {}
+                }
+            }
+        }
+
+        let comments: Vec<&WxrComment> = if ctx.options.comments {
+            let mut comments: Vec<&WxrComment> = item
+                .comments
+                .iter()
+                .filter(|c| matches!(c.comment_type.as_str(), "" | "comment"))
+                .filter(|c| map_comment_status(&c.approved).is_some())
+                .collect();
+            comments.sort_by_key(|c| c.id);
+            comments
+        } else {
+            Vec::new()
+        };
+
+        if ctx.options.dry_run {
+            match item.post_type.as_str() {
+                "page" => ctx.progress.pages += 1,
+                _ => ctx.progress.posts += 1,
+            }
+            ctx.progress.comments += comments.len() as i32;
+            return Ok(());
+        }
+
+        let author_id = ctx
+            .authors
+            .get(&item.creator)
+            .copied()
+            .unwrap_or(ctx.started_by);
+        let featured_image_id = item
+            .meta
+            .iter()
+            .find(|meta| meta.key == "_thumbnail_id")
+            .and_then(|meta| meta.value.trim().parse::<i64>().ok())
+            .and_then(|id| ctx.media.get(&id).copied());
+        let created_at = item
+            .post_date_gmt
+            .or(item.post_date)
+            .unwrap_or_else(Utc::now);
+        let updated_at = item
+            .post_modified_gmt
+            .or(item.post_modified)
+            .unwrap_or(created_at);
+        let published_at =
+            matches!(status, "published" | "scheduled" | "private").then_some(created_at);
+        let title = match item.title.trim() {
+            "" => "(no title)".to_string(),
+            title => truncate(title, 500),
+        };
+        let comment_status = if item.comment_status == "closed" {
+            "closed"
+        } else {
+            "open"
+        };
+        let meta = serde_json::json!({
+            "wordpress": {
+                "id": item.post_id,
+                "guid": item.guid,
+                "link": item.link,
+                "parent": item.post_parent,
+                "menu_order": item.menu_order,
+                "sticky": item.is_sticky,
+                "import_id": ctx.run_id,
+            }
+        });
+
+        let post_id = Uuid::now_v7();
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| Error::database_with_source("Failed to start transaction", e))?;
+
+        sqlx::query(
+            "INSERT INTO posts (id, title, slug, content, excerpt, status, post_type, author_id, \
+             featured_image_id, published_at, created_at, updated_at, meta, comment_status, \
+             site_id) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)",
+        )
+        .bind(post_id)
+        .bind(&title)
+        .bind(&slug)
+        .bind(rewrite_urls(&item.content, &ctx.urls))
+        .bind(Some(&item.excerpt).filter(|e| !e.is_empty()))
+        .bind(status)
+        .bind(&item.post_type)
+        .bind(author_id)
+        .bind(featured_image_id)
+        .bind(published_at)
+        .bind(created_at)
+        .bind(updated_at)
+        .bind(meta)
+        .bind(comment_status)
+        .bind(tenant::current_site())
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| Error::database_with_source("Failed to save post", e))?;
+
+        for category_id in &category_ids {
+            sqlx::query(
+                "INSERT INTO post_categories (post_id, category_id) VALUES ($1, $2) \
+                 ON CONFLICT DO NOTHING",
+            )
+            .bind(post_id)
+            .bind(category_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to link category", e))?;
+        }
+        for tag_id in &tag_ids {
+            sqlx::query(
+                "INSERT INTO post_tags (post_id, tag_id) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+            )
+            .bind(post_id)
+            .bind(tag_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to link tag", e))?;
+        }
+
+        let imported_comments = self
+            .import_comments(&mut tx, ctx, post_id, &comments)
+            .await?;
+
+        tx.commit()
+            .await
+            .map_err(|e| Error::database_with_source("Failed to save post", e))?;
+
+        match item.post_type.as_str() {
+            "page" => ctx.progress.pages += 1,
+            _ => ctx.progress.posts += 1,
+        }
+        ctx.progress.comments += imported_comments;
+        Ok(())
+    }
+
+    /// Insert a post's comments, oldest first so replies find their parent.
+    /// Depth and the post's comment count are kept by database triggers.
+    async fn import_comments(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        ctx: &ImportContext,
+        post_id: Uuid,
+        comments: &[&WxrComment],
+    ) -> Result<i32> {
+        let mut imported: HashMap<i64, Uuid> = HashMap::new();
+
+        for comment in comments {
+            let Some(status) = map_comment_status(&comment.approved) else {
+                continue;
+            };
+            let id = Uuid::now_v7();
+            let created_at = comment.date_gmt.or(comment.date).unwrap_or_else(Utc::now);
+
+            sqlx::query(
+                "INSERT INTO comments (id, post_id, parent_id, user_id, author_name, author_email, \
+                 author_url, author_ip, content, status, created_at, updated_at, site_id) \
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $11, $12)",
+            )
+            .bind(id)
+            .bind(post_id)
+            .bind(imported.get(&comment.parent).copied())
+            .bind(ctx.author_ids.get(&comment.user_id).copied())
+            .bind(Some(truncate(&comment.author, 255)).filter(|a| !a.is_empty()))
+            .bind(Some(truncate(&comment.author_email, 255)).filter(|e| !e.is_empty()))
+            .bind(Some(truncate(&comment.author_url, 500)).filter(|u| !u.is_empty()))
+            .bind(Some(truncate(&comment.author_ip, 45)).filter(|ip| !ip.is_empty()))
+            .bind(&comment.content)
+            .bind(status)
+            .bind(created_at)
+            .bind(tenant::current_site())
+            .execute(&mut **tx)
+            .await
+            .map_err(|e| Error::database_with_source("Failed to save comment", e))?;
+
+            imported.insert(comment.id, id);
+        }
+        Ok(imported.len() as i32)
+    }
+
+    async fn save_progress(&self, run_id: Uuid, progress: &ImportProgress, status: Option<&str>) {
+        let result = sqlx::query(
+            "UPDATE wordpress_imports SET processed = $2, authors = $3, categories = $4, \
+             tags = $5, posts = $6, pages = $7, media = $8, comments = $9, redirects = $10, \
+             skipped = $11, failed = $12, errors = $13, status = COALESCE($14, status), \
+             finished_at = CASE WHEN $14 IS NULL THEN finished_at ELSE NOW() END \
+             WHERE id = $1",
+        )
+        .bind(run_id)
+        .bind(progress.processed)
+        .bind(progress.authors)
+        .bind(progress.categories)
+        .bind(progress.tags)
+        .bind(progress.posts)
+        .bind(progress.pages)
+        .bind(progress.media)
+        .bind(progress.comments)
+        .bind(progress.redirects)
+        .bind(progress.skipped)
+        .bind(progress.failed)
+        .bind(Json(&progress.errors))
+        .bind(status)
+        .execute(&self.pool)
+        .await;
+        if let Err(e) = result {
+            tracing::error!(import_id = %run_id, "Failed to save WordPress import progress: {}", e);
+        }
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(post_type: &str, post_name: &str, title: &str) -> WxrItem {
+        WxrItem {
+            post_type: post_type.to_string(),
+            post_name: post_name.to_string(),
+            title: title.to_string(),
+            post_id: 42,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_status_mapping() {
+        assert_eq!(map_post_status("publish"), Some("published"));
+        assert_eq!(map_post_status("future"), Some("scheduled"));
+        assert_eq!(map_post_status("trash"), None);
+        assert_eq!(map_post_status("auto-draft"), None);
+        assert_eq!(map_comment_status("1"), Some("approved"));
+        assert_eq!(map_comment_status("spam"), Some("spam"));
+        assert_eq!(map_comment_status("trash"), None);
+    }
+
+    #[test]
+    fn test_slugs_and_paths() {
+        assert_eq!(item_slug(&item("post", "hello-world", "x")), "hello-world");
+        assert_eq!(item_slug(&item("post", "caf%c3%a9", "x")), "café");
+        assert_eq!(item_slug(&item("page", "", "About Us")), "about-us");
+        assert_eq!(item_slug(&item("post", "", "")), "post-42");
+        assert_eq!(content_path("page", "about"), "/page/about");
+        assert_eq!(content_path("post", "hello"), "/post/hello");
+        assert_eq!(
+            link_path("https://old.example.com/2024/01/15/hello/").as_deref(),
+            Some("/2024/01/15/hello")
+        );
+        assert_eq!(link_path("https://old.example.com/?p=12"), None);
+    }
+
+    #[test]
+    fn test_rewrite_urls() {
+        let urls = HashMap::from([
+            (
+                url_key("https://old.example.com/wp-content/uploads/2024/01/a.jpg"),
+                "/uploads/imports/wordpress/2024/01/a.jpg".to_string(),
+            ),
+            (
+                url_key("https://old.example.com/2024/01/15/hello/"),
+                "/post/hello".to_string(),
+            ),
+        ]);
+        let content = r#"<img src="http://old.example.com/wp-content/uploads/2024/01/a.jpg"> <a href="https://old.example.com/2024/01/15/hello">x</a> <a href="https://other.example.com/">y</a>"#;
+        assert_eq!(
+            rewrite_urls(content, &urls),
+            r#"<img src="/uploads/imports/wordpress/2024/01/a.jpg"> <a href="/post/hello">x</a> <a href="https://other.example.com/">y</a>"#
+        );
+    }
+
+    #[test]
+    fn test_helpers() {
+        assert_eq!(
+            decode_entities("News &amp; Notes &#039;24"),
+            "News & Notes '24"
+        );
+        assert_eq!(
+            media_type(Some("image/png; charset=x"), "a.jpg"),
+            "image/png"
+        );
+        assert_eq!(
+            media_type(Some("application/octet-stream"), "a.JPG"),
+            "image/jpeg"
+        );
+        assert_eq!(media_type(None, "a.unknown"), "application/octet-stream");
+        assert_eq!(csv_field("/a,b"), "\"/a,b\"");
+
+        assert!(is_public_ip("93.184.216.34".parse().unwrap()));
+        assert!(!is_public_ip("127.0.0.1".parse().unwrap()));
+        assert!(!is_public_ip("10.1.2.3".parse().unwrap()));
+        assert!(!is_public_ip("169.254.169.254".parse().unwrap()));
+        assert!(!is_public_ip("100.64.0.1".parse().unwrap()));
+        assert!(!is_public_ip("::1".parse().unwrap()));
+        assert!(!is_public_ip("fd00::1".parse().unwrap()));
+        assert!(!is_public_ip("::ffff:192.168.0.1".parse().unwrap()));
+    }
+}
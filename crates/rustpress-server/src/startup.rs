@@ -0,0 +1,584 @@
+//! Startup service container
+//!
+//! Services are registered with the services they depend on and an async
+//! constructor. A service is built the first time it is requested, after
+//! its dependencies, and services that do not depend on each other are
+//! built concurrently. The container records how long each constructor
+//! took, and can run a connectivity check per service for `--check`.
+
+use std::any::Any;
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use futures::future::{join_all, BoxFuture};
+use futures::FutureExt;
+use parking_lot::Mutex;
+use serde::Serialize;
+use tokio::sync::OnceCell;
+
+/// Error returned by service constructors and checks
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Errors building the service graph or a service
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum StartupError {
+    #[error("Service '{0}' is registered twice")]
+    Duplicate(String),
+
+    #[error("Service '{0}' is not registered")]
+    Unknown(String),
+
+    #[error("Service '{service}' depends on unregistered service '{dependency}'")]
+    MissingDependency { service: String, dependency: String },
+
+    #[error("Service '{service}' requested '{dependency}' without declaring it")]
+    UndeclaredDependency { service: String, dependency: String },
+
+    #[error("Services form a dependency cycle: {}", .0.join(" -> "))]
+    Cycle(Vec<String>),
+
+    #[error("Service '{0}' has a different type than requested")]
+    TypeMismatch(String),
+
+    #[error("Service '{service}' failed to start: {message}")]
+    Failed { service: String, message: String },
+}
+
+type Instance = Arc<dyn Any + Send + Sync>;
+type InitFn =
+    Box<dyn Fn(Dependencies) -> BoxFuture<'static, Result<Instance, BoxError>> + Send + Sync>;
+type CheckFn = Box<dyn Fn(Instance) -> BoxFuture<'static, Result<(), BoxError>> + Send + Sync>;
+
+struct ServiceEntry {
+    dependencies: Vec<String>,
+    init: InitFn,
+    check: Option<CheckFn>,
+    instance: OnceCell<Instance>,
+    outcome: Mutex<Option<ServiceOutcome>>,
+}
+
+#[derive(Clone)]
+struct ServiceOutcome {
+    duration: Duration,
+    error: Option<String>,
+}
+
+/// Registers services before the container is built
+#[derive(Default)]
+pub struct ServiceContainerBuilder {
+    services: BTreeMap<String, ServiceEntry>,
+    errors: Vec<StartupError>,
+}
+
+impl ServiceContainerBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a service built by `init` once `dependencies` are ready
+    pub fn service<T, F, Fut>(mut self, name: &str, dependencies: &[&str], init: F) -> Self
+    where
+        T: Send + Sync + 'static,
+        F: Fn(Dependencies) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<T, BoxError>> + Send + 'static,
+    {
+        if self.services.contains_key(name) {
+            self.errors.push(StartupError::Duplicate(name.to_string()));
+            return self;
+        }
+
+        let init: InitFn = Box::new(move |deps| {
+            init(deps)
+                .map(|result| result.map(|service| Arc::new(service) as Instance))
+                .boxed()
+        });
+        self.services.insert(
+            name.to_string(),
+            ServiceEntry {
+                dependencies: dependencies.iter().map(|d| d.to_string()).collect(),
+                init,
+                check: None,
+                instance: OnceCell::new(),
+                outcome: Mutex::new(None),
+            },
+        );
+        self
+    }
+
+    /// Register a service that is already built
+    pub fn value<T>(self, name: &str, value: T) -> Self
+    where
+        T: Clone + Send + Sync + 'static,
+    {
+        self.service(name, &[], move |_| {
+            let value = value.clone();
+            async move { Ok(value) }
+        })
+    }
+
+    /// Connectivity check run for the service in `--check` mode
+    pub fn check<T, F, Fut>(mut self, name: &str, check: F) -> Self
+    where
+        T: Send + Sync + 'static,
+        F: Fn(Arc<T>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), BoxError>> + Send + 'static,
+    {
+        let Some(entry) = self.services.get_mut(name) else {
+            self.errors.push(StartupError::Unknown(name.to_string()));
+            return self;
+        };
+
+        let service = name.to_string();
+        entry.check = Some(Box::new(move |instance| match instance.downcast::<T>() {
+            Ok(instance) => check(instance).boxed(),
+            Err(_) => {
+                let error = StartupError::TypeMismatch(service.clone());
+                async move { Err(error.into()) }.boxed()
+            }
+        }));
+        self
+    }
+
+    /// Validate the dependency graph and build the container
+    pub fn build(mut self) -> Result<ServiceContainer, StartupError> {
+        if !self.errors.is_empty() {
+            return Err(self.errors.remove(0));
+        }
+
+        for (name, entry) in &self.services {
+            for dependency in &entry.dependencies {
+                if !self.services.contains_key(dependency) {
+                    return Err(StartupError::MissingDependency {
+                        service: name.clone(),
+                        dependency: dependency.clone(),
+                    });
+                }
+            }
+        }
+        if let Some(cycle) = find_cycle(&self.services) {
+            return Err(StartupError::Cycle(cycle));
+        }
+
+        Ok(ServiceContainer {
+            services: Arc::new(self.services),
+        })
+    }
+}
+
+/// Services by name, built on first use
+#[derive(Clone)]
+pub struct ServiceContainer {
+    services: Arc<BTreeMap<String, ServiceEntry>>,
+}
+
+impl ServiceContainer {
+    pub fn builder() -> ServiceContainerBuilder {
+        ServiceContainerBuilder::new()
+    }
+
+    /// Registered service names
+    pub fn names(&self) -> Vec<&str> {
+        self.services.keys().map(String::as_str).collect()
+    }
+
+    /// Get a service, building it and its dependencies if needed
+    pub async fn get<T: Send + Sync + 'static>(&self, name: &str) -> Result<Arc<T>, StartupError> {
+        self.instance(name)
+            .await?
+            .downcast::<T>()
+            .map_err(|_| StartupError::TypeMismatch(name.to_string()))
+    }
+
+    /// Whether a service has been built
+    pub fn is_ready(&self, name: &str) -> bool {
+        self.services
+            .get(name)
+            .is_some_and(|entry| entry.instance.initialized())
+    }
+
+    /// Build every service, independent ones concurrently
+    pub async fn start_all(&self) -> Result<StartupReport, StartupError> {
+        let results = join_all(self.services.keys().map(|name| self.instance(name))).await;
+        let report = self.report();
+        for result in results {
+            result?;
+        }
+        Ok(report)
+    }
+
+    /// Build every service and run its connectivity check, without
+    /// stopping at the first failure
+    pub async fn check_all(&self) -> StartupReport {
+        let checks = self.services.iter().map(|(name, entry)| async move {
+            let instance = match self.instance(name).await {
+                Ok(instance) => instance,
+                Err(_) => return (name.clone(), None),
+            };
+            let Some(check) = &entry.check else {
+                return (name.clone(), None);
+            };
+
+            let start = Instant::now();
+            let result = check(instance).await.map_err(|e| e.to_string());
+            (name.clone(), Some((start.elapsed(), result)))
+        });
+        let checks: HashMap<String, _> = join_all(checks).await.into_iter().collect();
+
+        let mut report = self.report();
+        for service in &mut report.services {
+            if let Some(Some((duration, result))) = checks.get(&service.name) {
+                service.check_ms = Some(duration.as_millis() as u64);
+                if let Err(e) = result {
+                    service.status = ServiceStatus::CheckFailed;
+                    service.error = Some(e.clone());
+                }
+            }
+        }
+        report
+    }
+
+    /// Build time and state of every service so far
+    pub fn report(&self) -> StartupReport {
+        let services = self
+            .services
+            .iter()
+            .map(|(name, entry)| {
+                let outcome = entry.outcome.lock().clone();
+                let status = match &outcome {
+                    _ if entry.instance.initialized() => ServiceStatus::Ready,
+                    Some(ServiceOutcome { error: Some(_), .. }) => ServiceStatus::Failed,
+                    _ => ServiceStatus::Pending,
+                };
+                ServiceReport {
+                    name: name.clone(),
+                    dependencies: entry.dependencies.clone(),
+                    status,
+                    init_ms: outcome.as_ref().map(|o| o.duration.as_millis() as u64),
+                    check_ms: None,
+                    error: outcome.and_then(|o| o.error),
+                }
+            })
+            .collect();
+
+        StartupReport { services }
+    }
+
+    /// Build a service once; concurrent callers wait for the same build
+    fn instance<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<Instance, StartupError>> {
+        async move {
+            let entry = self
+                .services
+                .get(name)
+                .ok_or_else(|| StartupError::Unknown(name.to_string()))?;
+
+            entry
+                .instance
+                .get_or_try_init(|| async {
+                    // Dependencies first, concurrently; the timer only
+                    // covers this service's own constructor
+                    let dependencies =
+                        join_all(entry.dependencies.iter().map(|d| self.instance(d))).await;
+                    for dependency in dependencies {
+                        dependency?;
+                    }
+
+                    let start = Instant::now();
+                    let result = (entry.init)(Dependencies {
+                        container: self.clone(),
+                        service: name.to_string(),
+                    })
+                    .await;
+                    let duration = start.elapsed();
+
+                    let error = result.as_ref().err().map(|e| e.to_string());
+                    match &error {
+                        Some(e) => {
+                            tracing::error!(service = name, error = %e, "Service failed to start")
+                        }
+                        None => tracing::debug!(
+                            service = name,
+                            duration_ms = duration.as_millis() as u64,
+                            "Service started"
+                        ),
+                    }
+                    *entry.outcome.lock() = Some(ServiceOutcome {
+                        duration,
+                        error: error.clone(),
+                    });
+
+                    result.map_err(|_| StartupError::Failed {
+                        service: name.to_string(),
+                        message: error.unwrap_or_default(),
+                    })
+                })
+                .await
+                .cloned()
+        }
+        .boxed()
+    }
+}
+
+/// Access to the declared dependencies of the service being built
+pub struct Dependencies {
+    container: ServiceContainer,
+    service: String,
+}
+
+impl Dependencies {
+    /// Get a dependency; it must be listed in the service's registration
+    pub async fn get<T: Send + Sync + 'static>(&self, name: &str) -> Result<Arc<T>, StartupError> {
+        let declared = self
+            .container
+            .services
+            .get(&self.service)
+            .is_some_and(|entry| entry.dependencies.iter().any(|d| d == name));
+        if !declared {
+            return Err(StartupError::UndeclaredDependency {
+                service: self.service.clone(),
+                dependency: name.to_string(),
+            });
+        }
+
+        self.container.get(name).await
+    }
+}
+
+/// State of a service in a [`StartupReport`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ServiceStatus {
+    Pending,
+    Ready,
+    Failed,
+    CheckFailed,
+}
+
+/// Startup outcome of one service
+#[derive(Debug, Clone, Serialize)]
+pub struct ServiceReport {
+    pub name: String,
+    pub dependencies: Vec<String>,
+    pub status: ServiceStatus,
+    /// Time spent in the service's own constructor
+    pub init_ms: Option<u64>,
+    /// Time spent in its connectivity check
+    pub check_ms: Option<u64>,
+    pub error: Option<String>,
+}
+
+/// Startup outcome of every service
+#[derive(Debug, Clone, Serialize)]
+pub struct StartupReport {
+    pub services: Vec<ServiceReport>,
+}
+
+impl StartupReport {
+    /// Whether no service failed to start or its check
+    pub fn is_healthy(&self) -> bool {
+        self.services
+            .iter()
+            .all(|s| !matches!(s.status, ServiceStatus::Failed | ServiceStatus::CheckFailed))
+    }
+
+    /// Services slowest first
+    pub fn slowest(&self) -> Vec<&ServiceReport> {
+        let mut services: Vec<_> = self.services.iter().collect();
+        services.sort_by_key(|s| std::cmp::Reverse(s.init_ms.unwrap_or(0)));
+        services
+    }
+}
+
+impl std::fmt::Display for StartupReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{:<24} {:<13} {:>9} {:>9}",
+            "SERVICE", "STATUS", "INIT", "CHECK"
+        )?;
+        for service in &self.services {
+            let ms = |value: Option<u64>| value.map_or("-".to_string(), |ms| format!("{}ms", ms));
+            let status = match service.status {
+                ServiceStatus::Pending => "pending",
+                ServiceStatus::Ready => "ok",
+                ServiceStatus::Failed => "failed",
+                ServiceStatus::CheckFailed => "check failed",
+            };
+            writeln!(
+                f,
+                "{:<24} {:<13} {:>9} {:>9}",
+                service.name,
+                status,
+                ms(service.init_ms),
+                ms(service.check_ms)
+            )?;
+            if let Some(error) = &service.error {
+                writeln!(f, "    {}", error)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Services on a dependency cycle, if the graph has one
+fn find_cycle(services: &BTreeMap<String, ServiceEntry>) -> Option<Vec<String>> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum Mark {
+        Visiting,
+        Done,
+    }
+
+    fn visit<'a>(
+        name: &'a str,
+        services: &'a BTreeMap<String, ServiceEntry>,
+        marks: &mut HashMap<&'a str, Mark>,
+        path: &mut Vec<&'a str>,
+    ) -> Option<Vec<String>> {
+        match marks.get(name) {
+            Some(Mark::Done) => return None,
+            Some(Mark::Visiting) => {
+                let start = path.iter().position(|n| *n == name).unwrap_or(0);
+                let mut cycle: Vec<String> = path[start..].iter().map(|n| n.to_string()).collect();
+                cycle.push(name.to_string());
+                return Some(cycle);
+            }
+            None => {}
+        }
+
+        marks.insert(name, Mark::Visiting);
+        path.push(name);
+        for dependency in &services[name].dependencies {
+            if let Some(cycle) = visit(dependency, services, marks, path) {
+                return Some(cycle);
+            }
+        }
+        path.pop();
+        marks.insert(name, Mark::Done);
+        None
+    }
+
+    let mut marks = HashMap::new();
+    services
+        .keys()
+        .find_map(|name| visit(name, services, &mut marks, &mut Vec::new()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test]
+    async fn test_services_are_built_lazily_and_once() {
+        let builds = Arc::new(AtomicUsize::new(0));
+        let counter = builds.clone();
+        let container = ServiceContainer::builder()
+            .value("config", 40u32)
+            .service("answer", &["config"], move |deps| {
+                let counter = counter.clone();
+                async move {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                    Ok(*deps.get::<u32>("config").await? + 2)
+                }
+            })
+            .build()
+            .unwrap();
+
+        assert!(!container.is_ready("answer"));
+        let (a, b) = tokio::join!(
+            container.get::<u32>("answer"),
+            container.get::<u32>("answer")
+        );
+        assert_eq!(*a.unwrap(), 42);
+        assert_eq!(*b.unwrap(), 42);
+        assert_eq!(builds.load(Ordering::SeqCst), 1);
+        assert!(container.get::<String>("answer").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_independent_services_start_concurrently() {
+        let slow = |_| async {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            Ok(())
+        };
+        let container = ServiceContainer::builder()
+            .service("a", &[], slow)
+            .service("b", &[], slow)
+            .service("c", &[], slow)
+            .build()
+            .unwrap();
+
+        let start = Instant::now();
+        let report = container.start_all().await.unwrap();
+        assert!(start.elapsed() < Duration::from_millis(250));
+        assert!(report.is_healthy());
+        assert!(report.services.iter().all(|s| s.init_ms.is_some()));
+    }
+
+    #[test]
+    fn test_graph_validation() {
+        let missing = ServiceContainer::builder()
+            .service("a", &["b"], |_| async { Ok(()) })
+            .build();
+        assert!(matches!(
+            missing,
+            Err(StartupError::MissingDependency { .. })
+        ));
+
+        let cycle = ServiceContainer::builder()
+            .service("a", &["b"], |_| async { Ok(()) })
+            .service("b", &["a"], |_| async { Ok(()) })
+            .build();
+        match cycle {
+            Err(StartupError::Cycle(services)) => assert_eq!(services, vec!["a", "b", "a"]),
+            _ => panic!("expected a cycle"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_undeclared_dependency_is_rejected() {
+        let container = ServiceContainer::builder()
+            .value("config", 1u32)
+            .service("sneaky", &[], |deps| async move {
+                deps.get::<u32>("config").await?;
+                Ok(())
+            })
+            .build()
+            .unwrap();
+
+        assert!(container.get::<()>("sneaky").await.is_err());
+        assert_eq!(container.report().services[1].status, ServiceStatus::Failed);
+    }
+
+    #[tokio::test]
+    async fn test_check_all_reports_failures() {
+        let container = ServiceContainer::builder()
+            .value("database", ())
+            .check("database", |_: Arc<()>| async {
+                Err::<(), BoxError>("connection refused".into())
+            })
+            .service("broken", &[], |_| async {
+                Err::<(), BoxError>("bad configuration".into())
+            })
+            .value("cache", ())
+            .build()
+            .unwrap();
+
+        let report = container.check_all().await;
+        assert!(!report.is_healthy());
+
+        let status = |name: &str| {
+            report
+                .services
+                .iter()
+                .find(|s| s.name == name)
+                .map(|s| s.status)
+                .unwrap()
+        };
+        assert_eq!(status("database"), ServiceStatus::CheckFailed);
+        assert_eq!(status("broken"), ServiceStatus::Failed);
+        assert_eq!(status("cache"), ServiceStatus::Ready);
+        assert!(report.to_string().contains("connection refused"));
+    }
+}
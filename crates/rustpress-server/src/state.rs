@@ -3,18 +3,38 @@
 use rustpress_auth::{JwtManager, PermissionChecker};
 use rustpress_cache::Cache;
 use rustpress_core::config::AppConfig;
+use rustpress_core::fault::FaultInjector;
 use rustpress_core::hook::HookRegistry;
+use rustpress_core::http::HttpClient;
 use rustpress_core::plugin::PluginManager;
 use rustpress_database::{pool::DatabaseExecutor, DatabasePool};
-use rustpress_events::EventBus;
-use rustpress_jobs::JobQueue;
+use rustpress_events::{DomainEvent, EventBus};
+use rustpress_jobs::saga::PgSagaStore;
+use rustpress_jobs::{JobQueue, PauseSwitch, SagaCoordinator};
 use rustpress_storage::Storage;
+use rustpress_themes::ResponsiveImageGenerator;
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
-use crate::services::{EmailConfig, EmailService, RenderService, ThemeService};
+use crate::collab::CollabManager;
+use crate::plugin_routes::PluginRouteRegistry;
+use crate::security::{BotDetectionConfig, BotDetectionMiddleware};
+use crate::services::{
+    device_login_provider, AbuseChallengeService, AdminSearchService, AnalyticsExporter,
+    AvatarService, CachePolicyService, CacheWarmerService, CaptchaService, ChangeFeed,
+    ComplianceService, ContentFilterService, ContentSanitizationService, DeviceLoginProvider,
+    DiscussionService, EmailConfig, EmailService, EventService, ExtensionAllowlistService,
+    GeoIpService, GroupService, HttpSignatureService, InboundEmailService, IndexingService,
+    MenuService, OgImageService, PageCacheService, PodcastService, ProfileService,
+    PublicApiService, ReadOnlyService, RedirectService, RenderService, ResponsiveImagesService,
+    SamlSsoService, SearchService, SettingsChange, SettingsSync, SiteBundleService, SiteService,
+    SocialService, TaxonomyService, ThemeService, UsageService, UserApiKeyService,
+    UserImportService, WarmTarget, WebVitalsService, WebhookService, WidgetService,
+    WordpressImportService,
+};
 use crate::websocket::WebSocketHub;
+use crate::ws::ConnectionManager;
 
 /// Application state shared across all requests
 #[derive(Clone)]
@@ -29,6 +49,8 @@ pub struct AppState {
     pub event_bus: Arc<EventBus>,
     /// Job queue for background tasks
     pub job_queue: Arc<JobQueue>,
+    /// Multi-step operations with compensation, persisted in the database
+    pub sagas: Arc<SagaCoordinator>,
     /// File storage
     pub storage: Arc<Storage>,
     /// JWT manager for token operations
@@ -39,6 +61,11 @@ pub struct AppState {
     pub hooks: Arc<RwLock<HookRegistry>>,
     /// Plugin manager
     pub plugins: Arc<RwLock<PluginManager>>,
+    /// Routers plugins mount under `/plugins/{id}/`
+    pub plugin_routes: Arc<PluginRouteRegistry>,
+    /// Host running WebAssembly plugins installed at runtime
+    #[cfg(feature = "wasm-plugins")]
+    pub wasm_plugins: Arc<rustpress_plugins::WasmPluginHost>,
     /// Theme service for theme management
     pub theme_service: Arc<ThemeService>,
     /// Render service for public-facing pages
@@ -47,6 +74,101 @@ pub struct AppState {
     pub email_service: Arc<EmailService>,
     /// WebSocket hub for real-time collaboration
     pub ws_hub: Arc<WebSocketHub>,
+    /// Bot detection shared by the middleware stack and stats endpoints
+    pub bot_detection: BotDetectionMiddleware,
+    /// Honeypot, time-trap and proof-of-work challenges for public forms
+    pub abuse_challenges: Arc<AbuseChallengeService>,
+    /// CAPTCHA provider verification for public forms
+    pub captcha: Arc<CaptchaService>,
+    /// Cache-Control and surrogate key policy for public responses
+    pub cache_policy: Arc<CachePolicyService>,
+    /// Rendered public pages and surrogate-key purging
+    pub page_cache: Arc<PageCacheService>,
+    /// Pre-renders popular pages after deploys and publishes
+    pub cache_warmer: Arc<CacheWarmerService>,
+    /// Local GeoIP database lookups
+    pub geoip: Arc<GeoIpService>,
+    /// Per-region cookie banner, age gate and content blocking rules
+    pub compliance: Arc<ComplianceService>,
+    /// Profile fields and avatar choice
+    pub profiles: Arc<ProfileService>,
+    /// Avatar uploads, Gravatar proxy and initials fallback
+    pub avatars: Arc<AvatarService>,
+    /// Live dashboard notification connections
+    pub live: Arc<ConnectionManager>,
+    /// Admin command palette search
+    pub admin_search: Arc<AdminSearchService>,
+    /// Read-only public API keys, limits and usage
+    pub public_api: Arc<PublicApiService>,
+    /// HTTP message signature keys, verification and signing
+    pub http_signatures: Arc<HttpSignatureService>,
+    /// Per-role HTML sanitization of stored content and its reports
+    pub sanitization: Arc<ContentSanitizationService>,
+    /// Typography, emoji and shortlink filters run on rendered content
+    pub content_filters: Arc<ContentFilterService>,
+    /// Themes and plugins the network, each tenant and each site may use
+    pub extension_allowlists: Arc<ExtensionAllowlistService>,
+    /// OAuth2 device authorization grant for CLI login
+    pub device_login: Arc<DeviceLoginProvider>,
+    /// SAML single sign-on with the configured identity providers
+    pub saml_sso: Arc<SamlSsoService>,
+    /// Scoped API keys that act as their owner, for scripts and remote CLI use
+    pub user_api_keys: Arc<UserApiKeyService>,
+    /// Bulk user imports with role mapping and invitation emails
+    pub user_imports: Arc<UserImportService>,
+    /// User groups granting roles and capabilities and owning content sections
+    pub groups: Arc<GroupService>,
+    /// WordPress (WXR) imports with media sideloading
+    pub wordpress_imports: Arc<WordpressImportService>,
+    /// Old paths mapped to their new location, answered by the router's fallback
+    pub redirects: Arc<RedirectService>,
+    /// Internal editorial discussions on drafts
+    pub discussions: Arc<DiscussionService>,
+    /// Live collaborative editing sessions on posts
+    pub collab: Arc<CollabManager>,
+    /// Social preview cards and sharing posts to social networks
+    pub social: Arc<SocialService>,
+    /// Open Graph images of posts, rendered from their preview cards
+    pub og_images: Arc<OgImageService>,
+    /// Podcast show settings, episode enclosures and download statistics
+    pub podcast: Arc<PodcastService>,
+    /// Event schedules, venues and calendar views
+    pub events: Arc<EventService>,
+    /// Taxonomy registry, terms and bulk term assignments
+    pub taxonomies: Arc<TaxonomyService>,
+    /// Navigation menus and the theme locations they fill
+    pub menus: Arc<MenuService>,
+    /// Widget areas of themes and the widgets placed into them
+    pub widgets: Arc<WidgetService>,
+    /// Sites of the network and the plugins each activated
+    pub sites: Arc<SiteService>,
+    /// Quotas of network sites and the storage, bandwidth and job time
+    /// each uses
+    pub usage: Arc<UsageService>,
+    /// Maintenance mode refusing writes and pausing background jobs
+    pub read_only: Arc<ReadOnlyService>,
+    /// Drops cached settings when any node changes them
+    pub settings_sync: Arc<SettingsSync>,
+    /// Republishes content writes made on any node as `change.*` events
+    pub change_feed: Arc<ChangeFeed>,
+    /// Full-text search over posts, pages and media
+    pub search: Arc<SearchService>,
+    /// Site export bundles and the plugin data they carry
+    pub site_bundles: Arc<SiteBundleService>,
+    /// Outbound webhook endpoints and their deliveries
+    pub webhooks: Arc<WebhookService>,
+    /// Search engine indexing notifications and site verification
+    pub indexing: Arc<IndexingService>,
+    /// Core Web Vitals field data and performance budgets
+    pub web_vitals: Arc<WebVitalsService>,
+    /// Mailboxes whose messages become draft posts
+    pub inbound_email: Arc<InboundEmailService>,
+    /// Render-time image loading, dimensions and resized versions
+    pub responsive_images: Arc<ResponsiveImagesService>,
+    /// Latency and error injection rules (development and staging only)
+    pub faults: FaultInjector,
+    /// Shared outbound HTTP client with per-destination policies
+    pub http: HttpClient,
 }
 
 impl AppState {
@@ -75,6 +197,14 @@ impl AppState {
         &self.event_bus
     }
 
+    /// Publish a domain event; delivery problems are logged, not returned
+    pub async fn publish(&self, event: DomainEvent) {
+        let event_type = event.event_type.clone();
+        if let Err(e) = self.event_bus.publish(event).await {
+            tracing::warn!(%event_type, "Failed to publish event: {}", e);
+        }
+    }
+
     /// Get the job queue
     pub fn jobs(&self) -> &JobQueue {
         &self.job_queue
@@ -114,22 +244,152 @@ impl AppState {
     pub fn ws_hub(&self) -> &Arc<WebSocketHub> {
         &self.ws_hub
     }
+
+    /// Drop each service's cached settings when they change on any node
+    fn watch_settings(&self) {
+        use crate::services::{
+            abuse_challenge, cache_policy, cache_warmer, captcha, compliance, content_filters,
+            content_sanitization, geoip, indexing, page_cache, podcast, read_only, regions,
+            responsive_images, robots, saml_sso, social, user_profile, web_vitals,
+        };
+        let sync = &self.settings_sync;
+        let setting = SettingsChange::setting;
+
+        sync.watch(
+            setting(abuse_challenge::ABUSE_CHALLENGE_SETTINGS_KEY),
+            self.abuse_challenges.clone(),
+            |service| async move { service.invalidate().await },
+        );
+        sync.watch(
+            setting(captcha::CAPTCHA_SETTINGS_KEY),
+            self.captcha.clone(),
+            |service| async move { service.invalidate().await },
+        );
+        sync.watch(
+            setting(saml_sso::SAML_SSO_SETTINGS_KEY),
+            self.saml_sso.clone(),
+            |service| async move { service.invalidate().await },
+        );
+        sync.watch(
+            setting(cache_policy::CACHE_POLICY_SETTINGS_KEY),
+            self.cache_policy.clone(),
+            |service| async move { service.invalidate().await },
+        );
+        sync.watch(
+            setting(page_cache::PAGE_CACHE_SETTINGS_KEY),
+            self.page_cache.clone(),
+            |service| async move { service.invalidate().await },
+        );
+        sync.watch(
+            setting(cache_warmer::CACHE_WARMER_SETTINGS_KEY),
+            self.cache_warmer.clone(),
+            |service| async move { service.invalidate().await },
+        );
+        sync.watch(
+            setting(compliance::COMPLIANCE_SETTINGS_KEY),
+            self.compliance.clone(),
+            |service| async move { service.invalidate().await },
+        );
+        sync.watch(
+            setting(user_profile::PROFILE_FIELDS_SETTINGS_KEY),
+            self.profiles.clone(),
+            |service| async move { service.invalidate().await },
+        );
+        sync.watch(
+            setting(content_sanitization::SANITIZATION_SETTINGS_KEY),
+            self.sanitization.clone(),
+            |service| async move { service.invalidate().await },
+        );
+        sync.watch(
+            setting(content_filters::CONTENT_FILTERS_SETTINGS_KEY),
+            self.content_filters.clone(),
+            |service| async move { service.invalidate() },
+        );
+        sync.watch(
+            setting(social::SOCIAL_SETTINGS_KEY),
+            self.social.clone(),
+            |service| async move { service.invalidate().await },
+        );
+        sync.watch(
+            setting(indexing::INDEXING_SETTINGS_KEY),
+            self.indexing.clone(),
+            |service| async move { service.invalidate().await },
+        );
+        sync.watch(
+            setting(podcast::PODCAST_SETTINGS_KEY),
+            self.podcast.clone(),
+            |service| async move { service.invalidate().await },
+        );
+        sync.watch(
+            setting(web_vitals::WEB_VITALS_SETTINGS_KEY),
+            self.web_vitals.clone(),
+            |service| async move { service.invalidate().await },
+        );
+        sync.watch(
+            setting(responsive_images::RESPONSIVE_IMAGES_SETTINGS_KEY),
+            self.responsive_images.clone(),
+            |service| async move { service.invalidate().await },
+        );
+        sync.watch(
+            setting(robots::ROBOTS_SETTINGS_KEY),
+            self.render_service.clone(),
+            |service| async move { service.invalidate_robots_config().await },
+        );
+        sync.watch(
+            setting(regions::REGION_MAPPING_SETTINGS_KEY),
+            self.render_service.clone(),
+            |service| async move { service.invalidate_region_mapping().await },
+        );
+
+        // GeoIP, the allowlists and read-only mode are applied eagerly, so
+        // reload them
+        sync.watch(
+            setting(read_only::READ_ONLY_SETTINGS_KEY),
+            self.read_only.clone(),
+            |service| async move {
+                if let Err(e) = service.load().await {
+                    tracing::warn!("Failed to reload read-only mode: {}", e);
+                }
+            },
+        );
+        sync.watch(
+            setting(geoip::GEOIP_SETTINGS_KEY),
+            self.geoip.clone(),
+            |service| async move {
+                if let Err(e) = service.load().await {
+                    tracing::warn!("Failed to reload GeoIP settings: {}", e);
+                }
+            },
+        );
+        sync.watch(
+            SettingsChange::Allowlists,
+            self.extension_allowlists.clone(),
+            |service| async move {
+                if let Err(e) = service.load().await {
+                    tracing::warn!("Failed to reload extension allowlists: {}", e);
+                }
+            },
+        );
+    }
 }
 
 /// Builder for AppState
 pub struct AppStateBuilder {
     config: Option<AppConfig>,
-    database: Option<DatabasePool>,
-    cache: Option<Cache>,
-    event_bus: Option<EventBus>,
-    job_queue: Option<JobQueue>,
-    storage: Option<Storage>,
-    jwt: Option<JwtManager>,
+    database: Option<Arc<DatabasePool>>,
+    cache: Option<Arc<Cache>>,
+    event_bus: Option<Arc<EventBus>>,
+    job_queue: Option<Arc<JobQueue>>,
+    storage: Option<Arc<Storage>>,
+    usage: Option<Arc<UsageService>>,
+    jwt: Option<Arc<JwtManager>>,
     permissions: Option<PermissionChecker>,
     hooks: Option<HookRegistry>,
     plugins: Option<PluginManager>,
     themes_dir: Option<PathBuf>,
     email_config: Option<EmailConfig>,
+    bot_detection: Option<BotDetectionConfig>,
+    faults: Option<FaultInjector>,
 }
 
 impl AppStateBuilder {
@@ -141,12 +401,15 @@ impl AppStateBuilder {
             event_bus: None,
             job_queue: None,
             storage: None,
+            usage: None,
             jwt: None,
             permissions: None,
             hooks: None,
             plugins: None,
             themes_dir: None,
             email_config: None,
+            bot_detection: None,
+            faults: None,
         }
     }
 
@@ -155,33 +418,39 @@ impl AppStateBuilder {
         self
     }
 
-    pub fn database(mut self, database: DatabasePool) -> Self {
-        self.database = Some(database);
+    pub fn database(mut self, database: impl Into<Arc<DatabasePool>>) -> Self {
+        self.database = Some(database.into());
+        self
+    }
+
+    pub fn cache(mut self, cache: impl Into<Arc<Cache>>) -> Self {
+        self.cache = Some(cache.into());
         self
     }
 
-    pub fn cache(mut self, cache: Cache) -> Self {
-        self.cache = Some(cache);
+    pub fn event_bus(mut self, event_bus: impl Into<Arc<EventBus>>) -> Self {
+        self.event_bus = Some(event_bus.into());
         self
     }
 
-    pub fn event_bus(mut self, event_bus: EventBus) -> Self {
-        self.event_bus = Some(event_bus);
+    pub fn job_queue(mut self, job_queue: impl Into<Arc<JobQueue>>) -> Self {
+        self.job_queue = Some(job_queue.into());
         self
     }
 
-    pub fn job_queue(mut self, job_queue: JobQueue) -> Self {
-        self.job_queue = Some(job_queue);
+    pub fn storage(mut self, storage: impl Into<Arc<Storage>>) -> Self {
+        self.storage = Some(storage.into());
         self
     }
 
-    pub fn storage(mut self, storage: Storage) -> Self {
-        self.storage = Some(storage);
+    /// Usage meter shared with the storage; one is created when not given
+    pub fn usage(mut self, usage: Arc<UsageService>) -> Self {
+        self.usage = Some(usage);
         self
     }
 
-    pub fn jwt(mut self, jwt: JwtManager) -> Self {
-        self.jwt = Some(jwt);
+    pub fn jwt(mut self, jwt: impl Into<Arc<JwtManager>>) -> Self {
+        self.jwt = Some(jwt.into());
         self
     }
 
@@ -210,10 +479,25 @@ impl AppStateBuilder {
         self
     }
 
+    pub fn bot_detection(mut self, config: BotDetectionConfig) -> Self {
+        self.bot_detection = Some(config);
+        self
+    }
+
+    pub fn faults(mut self, faults: FaultInjector) -> Self {
+        self.faults = Some(faults);
+        self
+    }
+
     /// Build the AppState
     pub fn build(self) -> Result<AppState, &'static str> {
         let database = self.database.ok_or("database is required")?;
         let themes_dir = self.themes_dir.unwrap_or_else(|| PathBuf::from("./themes"));
+        let config = self.config.ok_or("config is required")?;
+
+        // Create the outbound HTTP client shared by every service
+        let http = HttpClient::new(config.http.clone())
+            .map_err(|_| "invalid outbound HTTP configuration")?;
 
         // Create theme service
         let theme_service = Arc::new(ThemeService::new(
@@ -222,33 +506,346 @@ impl AppStateBuilder {
             None, // site_id for multi-site support
         ));
 
+        // Create profile field and avatar services
+        let storage = self.storage.ok_or("storage is required")?;
+        let profiles = Arc::new(ProfileService::new(database.pool().clone()));
+        let avatars = Arc::new(AvatarService::new(
+            storage.clone(),
+            profiles.clone(),
+            http.clone(),
+        ));
+
+        // Create content filters; the built-ins register themselves on the
+        // hook registry before it is shared
+        let hooks = Arc::new(RwLock::new(self.hooks.unwrap_or_else(HookRegistry::new)));
+        let content_filters = Arc::new(ContentFilterService::new(
+            database.pool().clone(),
+            hooks.clone(),
+        ));
+
         // Create render service
-        let render_service = Arc::new(RenderService::new(
+        let menus = Arc::new(MenuService::new(
+            database.pool().clone(),
+            theme_service.clone(),
+        ));
+        let widgets = Arc::new(WidgetService::new(
             database.pool().clone(),
             theme_service.clone(),
-            themes_dir,
+            menus.clone(),
         ));
+        let sites = Arc::new(SiteService::new(database.pool().clone()));
+        let usage = self
+            .usage
+            .unwrap_or_else(|| Arc::new(UsageService::new(database.pool().clone())));
+        // Create Core Web Vitals; rendered pages carry its measuring script
+        let event_bus = self.event_bus.ok_or("event_bus is required")?;
+        let web_vitals =
+            Arc::new(WebVitalsService::new(database.pool().clone()).with_events(event_bus.clone()));
+        // Create responsive images; versions are kept in storage, the
+        // generator only holds the registered sizes
+        let responsive_images = Arc::new(ResponsiveImagesService::new(
+            database.pool().clone(),
+            storage.clone(),
+            Arc::new(ResponsiveImageGenerator::new(
+                std::env::temp_dir().join("rustpress-image-sizes"),
+            )),
+        ));
+        let render_service = Arc::new(
+            RenderService::new(database.pool().clone(), theme_service.clone(), themes_dir)
+                .with_profiles(profiles.clone())
+                .with_content_filters(content_filters.clone())
+                .with_menus(menus.clone())
+                .with_widgets(widgets.clone())
+                .with_web_vitals(web_vitals.clone())
+                .with_responsive_images(responsive_images.clone()),
+        );
 
         // Create email service
         let email_service = Arc::new(EmailService::new());
         // Email configuration will be applied at runtime via configure()
 
-        Ok(AppState {
-            config: Arc::new(self.config.ok_or("config is required")?),
-            database: Arc::new(database),
-            cache: Arc::new(self.cache.ok_or("cache is required")?),
-            event_bus: Arc::new(self.event_bus.ok_or("event_bus is required")?),
-            job_queue: Arc::new(self.job_queue.ok_or("job_queue is required")?),
-            storage: Arc::new(self.storage.ok_or("storage is required")?),
-            jwt: Arc::new(self.jwt.ok_or("jwt is required")?),
-            permissions: Arc::new(self.permissions.unwrap_or_else(PermissionChecker::default)),
-            hooks: Arc::new(RwLock::new(self.hooks.unwrap_or_else(HookRegistry::new))),
-            plugins: Arc::new(RwLock::new(self.plugins.unwrap_or_else(PluginManager::new))),
+        let cache = self.cache.ok_or("cache is required")?;
+
+        // Create anti-abuse challenge service; consumed tokens are shared
+        // through the cache
+        let abuse_challenges = Arc::new(AbuseChallengeService::new(
+            database.pool().clone(),
+            cache.clone(),
+            &config.auth.jwt_secret,
+        ));
+
+        // Create CAPTCHA service
+        let captcha = Arc::new(CaptchaService::new(database.pool().clone(), http.clone()));
+
+        // Create GeoIP service; databases are opened by `GeoIpService::load`
+        let geoip = Arc::new(GeoIpService::new(database.pool().clone(), http.clone()));
+
+        // Create edge cache policy service
+        let cache_policy = Arc::new(CachePolicyService::new(database.pool().clone()));
+
+        // Create full-page cache on top of the shared cache
+        let page_cache = Arc::new(PageCacheService::new(
+            database.pool().clone(),
+            cache.clone(),
+        ));
+
+        // Create cache warmer; it requests pages from this server's listener
+        let cache_warmer = Arc::new(CacheWarmerService::new(
+            database.pool().clone(),
+            render_service.clone(),
+            WarmTarget {
+                host: config.server.host.clone(),
+                port: config.server.port,
+                tls: config.server.tls_enabled,
+            },
+            http.clone(),
+        ));
+
+        // Create regional compliance rules service
+        let compliance = Arc::new(ComplianceService::new(
+            database.pool().clone(),
+            geoip.clone(),
+        ));
+
+        // Create admin command palette search; plugin pages are registered
+        // once plugins are loaded
+        let admin_search = Arc::new(AdminSearchService::new(database.pool().clone()));
+
+        // Create public API tier; responses and rate counters share the cache
+        let public_api = Arc::new(PublicApiService::new(
+            database.pool().clone(),
+            cache.clone(),
+        ));
+
+        // Create HTTP message signatures; nonces are shared through the cache
+        // and outbound keys are sealed with a key derived from the JWT secret
+        let http_signatures = Arc::new(HttpSignatureService::new(
+            database.pool().clone(),
+            cache.clone(),
+            &config.auth.jwt_secret,
+        ));
+
+        // Create content sanitization; the policy is loaded on first use
+        let sanitization = Arc::new(ContentSanitizationService::new(database.pool().clone()));
+
+        // Create theme and plugin allowlists; `load` pushes the network list
+        // into the theme and plugin managers
+        let plugins = Arc::new(RwLock::new(self.plugins.unwrap_or_else(PluginManager::new)));
+        let extension_allowlists = Arc::new(ExtensionAllowlistService::new(
+            database.pool().clone(),
+            plugins.clone(),
+            theme_service.file_manager().clone(),
+        ));
+
+        // Create device login provider with the CLI as a public client;
+        // device codes are shared between instances through the database
+        let device_login = Arc::new(device_login_provider(database.pool().clone()));
+
+        // Create SAML single sign-on; pending logins are kept in the database
+        let saml_sso = Arc::new(SamlSsoService::new(database.pool().clone()));
+
+        // Create user API keys, accepted in place of session tokens
+        let user_api_keys = Arc::new(UserApiKeyService::new(database.pool().clone()));
+
+        // Create user imports, inviting imported users by email
+        let user_imports = Arc::new(UserImportService::new(
+            database.pool().clone(),
+            email_service.clone(),
+        ));
+
+        // Create user groups; their grants are merged into each request's user
+        let groups = Arc::new(GroupService::new(database.pool().clone()));
+
+        // Create WordPress imports and the redirects they leave behind
+        let wordpress_imports = Arc::new(WordpressImportService::new(
+            database.pool().clone(),
+            storage.clone(),
+            http.clone(),
+        ));
+        let redirects = Arc::new(RedirectService::new(database.pool().clone()));
+
+        // Create editorial discussions, kept apart from public comments
+        let discussions = Arc::new(DiscussionService::new(database.pool().clone()));
+
+        // Create collaborative editing sessions, started as editors open posts
+        let collab = Arc::new(CollabManager::new(database.pool().clone()));
+
+        // Create read-only switch; the job worker is started with its pause
+        let read_only = Arc::new(ReadOnlyService::new(
+            database.pool().clone(),
+            PauseSwitch::new(),
+        ));
+
+        // Create settings sync; the listener is started with the server
+        let settings_sync = Arc::new(SettingsSync::new(database.pool().clone()));
+
+        // Create change feed; the listener is started with the server
+        let change_feed = Arc::new(ChangeFeed::new(database.pool().clone(), event_bus.clone()));
+
+        // Create search; the index follows the change feed
+        let search = Arc::new(
+            SearchService::from_config(database.pool().clone(), http.clone(), &config.search)
+                .map_err(|_| "invalid search configuration")?,
+        );
+        search.subscribe(&event_bus);
+
+        // Create social sharing; published posts are queued from the change feed
+        let social = Arc::new(
+            SocialService::new(
+                database.pool().clone(),
+                http.clone(),
+                render_service.clone(),
+            )
+            .with_events(event_bus.clone()),
+        );
+        social.subscribe(&event_bus);
+
+        // Create Open Graph images; published posts get theirs from the change feed
+        let og_images = Arc::new(OgImageService::new(
+            database.pool().clone(),
+            storage.clone(),
+            social.clone(),
+        ));
+        og_images.subscribe(&event_bus);
+
+        // Create search engine indexing; changed posts are queued from the change feed
+        let indexing = Arc::new(IndexingService::new(
+            database.pool().clone(),
+            http.clone(),
+            render_service.clone(),
+        ));
+        indexing.subscribe(&event_bus);
+
+        let podcast = Arc::new(PodcastService::new(
+            database.pool().clone(),
+            storage.clone(),
+        ));
+
+        let events = Arc::new(EventService::new(database.pool().clone()));
+        let taxonomies = Arc::new(TaxonomyService::new(
+            database.pool().clone(),
+            redirects.clone(),
+        ));
+
+        // Create site bundles; analytics data lives in the plugin's tables
+        let site_bundles = Arc::new(SiteBundleService::new());
+        site_bundles.register(Arc::new(AnalyticsExporter::new(database.pool().clone())));
+
+        // Create the saga coordinator; sagas are persisted so admins can
+        // inspect them from any instance
+        let sagas = Arc::new(SagaCoordinator::new(Arc::new(PgSagaStore::new(
+            database.pool().clone(),
+        ))));
+
+        // Create webhooks; events are fanned out once the server starts
+        let job_queue = self.job_queue.ok_or("job_queue is required")?;
+        let webhooks = Arc::new(WebhookService::new(
+            database.pool().clone(),
+            http.clone(),
+            job_queue.clone(),
+            http_signatures.clone(),
+        ));
+
+        // Create inbound email; drafts are written as the sender, sanitized as
+        // untrusted content
+        let permissions = Arc::new(self.permissions.unwrap_or_else(PermissionChecker::default));
+        let inbound_email = Arc::new(InboundEmailService::new(
+            database.pool().clone(),
+            storage.clone(),
+            sanitization.clone(),
+            permissions.clone(),
+            http.clone(),
+        ));
+
+        // Classify bots by the client address the configured proxies report
+        let bot_detection = BotDetectionMiddleware::new(self.bot_detection.unwrap_or_default())
+            .with_geoip(geoip.clone())
+            .with_trusted_proxies(config.server.trusted_proxies.clone());
+
+        // Create the WebAssembly plugin host; its plugins' hooks, routes
+        // and settings live in registries of their own
+        #[cfg(feature = "wasm-plugins")]
+        let wasm_plugins = Arc::new(
+            rustpress_plugins::WasmPluginHost::new(
+                Arc::new(rustpress_plugins::HookRegistry::new()),
+                Arc::new(rustpress_plugins::api::ApiRegistry::new()),
+                Arc::new(rustpress_plugins::SettingsManager::new()),
+            )
+            .map_err(|e| {
+                tracing::error!("Failed to start the WASM plugin host: {}", e);
+                "failed to start the WASM plugin host"
+            })?,
+        );
+
+        let state = AppState {
+            config: Arc::new(config),
+            database,
+            cache,
+            event_bus,
+            job_queue,
+            sagas,
+            storage,
+            jwt: self.jwt.ok_or("jwt is required")?,
+            permissions,
+            hooks,
+            plugins,
+            plugin_routes: Arc::new(PluginRouteRegistry::new()),
+            #[cfg(feature = "wasm-plugins")]
+            wasm_plugins,
             theme_service,
             render_service,
             email_service,
             ws_hub: WebSocketHub::new(),
-        })
+            bot_detection,
+            abuse_challenges,
+            captcha,
+            cache_policy,
+            page_cache,
+            cache_warmer,
+            geoip,
+            compliance,
+            profiles,
+            avatars,
+            live: ConnectionManager::new(),
+            admin_search,
+            public_api,
+            http_signatures,
+            sanitization,
+            content_filters,
+            extension_allowlists,
+            device_login,
+            saml_sso,
+            user_api_keys,
+            user_imports,
+            groups,
+            wordpress_imports,
+            redirects,
+            discussions,
+            collab,
+            social,
+            og_images,
+            podcast,
+            events,
+            taxonomies,
+            menus,
+            widgets,
+            sites,
+            usage,
+            read_only,
+            settings_sync,
+            change_feed,
+            search,
+            site_bundles,
+            webhooks,
+            indexing,
+            web_vitals,
+            inbound_email,
+            responsive_images,
+            faults: self.faults.unwrap_or_default(),
+            http,
+        };
+        state.watch_settings();
+        Ok(state)
     }
 }
 
@@ -257,3 +854,35 @@ impl Default for AppStateBuilder {
         Self::new()
     }
 }
+
+#[cfg(test)]
+impl AppState {
+    /// State backed by an in-memory cache and a database that is never
+    /// reached unless a handler needs it
+    pub(crate) fn for_tests(config: AppConfig) -> AppState {
+        use rustpress_auth::{JwtConfig, JwtManager};
+        use rustpress_cache::MemoryBackend;
+        use rustpress_database::pool::PoolConfig;
+        use rustpress_storage::LocalBackend;
+
+        let database = DatabasePool::connect_lazy(PoolConfig {
+            url: "postgres://localhost/rustpress_test".to_string(),
+            connect_timeout: std::time::Duration::from_millis(100),
+            ..Default::default()
+        })
+        .unwrap();
+
+        AppState::builder()
+            .config(config)
+            .job_queue(JobQueue::new(database.inner().clone()))
+            .database(database)
+            .cache(Cache::new(Arc::new(MemoryBackend::new(10_000))))
+            .event_bus(EventBus::new())
+            .storage(Storage::new(Arc::new(LocalBackend::new(
+                std::env::temp_dir().join("rustpress-test-storage"),
+            ))))
+            .jwt(JwtManager::new(JwtConfig::default()))
+            .build()
+            .unwrap()
+    }
+}
@@ -0,0 +1,293 @@
+//! Request-scoped database transactions
+//!
+//! The `transactions` middleware gives every mutating request (POST, PUT,
+//! PATCH, DELETE) a [`RequestTransaction`]. Handlers that take the
+//! [`RequestTx`] extractor run their statements in it, and the middleware
+//! commits once the handler answered with a 2xx and rolls back otherwise,
+//! so a handler failing halfway through several writes leaves none of them
+//! behind.
+//!
+//! The transaction begins on first use, so requests whose handlers never
+//! ask for it don't touch the database. Long-running routes opt out with
+//! the [`without_request_transaction`] route layer; their handlers get
+//! connections in autocommit mode instead, as do handlers on routes the
+//! middleware doesn't cover.
+
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use axum::{
+    async_trait,
+    body::Body,
+    extract::{FromRef, FromRequestParts, State},
+    http::{request::Parts, Method, Request},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use rustpress_core::error::{Error, Result};
+use sqlx::pool::PoolConnection;
+use sqlx::{PgConnection, PgPool, Postgres};
+use tokio::sync::{MappedMutexGuard, Mutex, MutexGuard};
+
+use crate::error::HttpError;
+use crate::state::AppState;
+
+enum TxState {
+    /// Not begun; nothing ran in it yet
+    Idle,
+    Open(Box<sqlx::Transaction<'static, Postgres>>),
+    /// Committed or rolled back
+    Finished,
+}
+
+/// The transaction of one request
+pub struct RequestTransaction {
+    pool: PgPool,
+    state: Mutex<TxState>,
+    autocommit: AtomicBool,
+}
+
+impl RequestTransaction {
+    fn new(pool: PgPool, autocommit: bool) -> Self {
+        Self {
+            pool,
+            state: Mutex::new(TxState::Idle),
+            autocommit: AtomicBool::new(autocommit),
+        }
+    }
+
+    /// Whether statements commit one by one instead
+    pub fn is_autocommit(&self) -> bool {
+        self.autocommit.load(Ordering::SeqCst)
+    }
+
+    /// Connection to run statements on, beginning the transaction on first
+    /// use. Holding it keeps other users of the same request waiting
+    pub async fn conn(&self) -> Result<TxConnection<'_>> {
+        if self.is_autocommit() {
+            let conn = self.pool.acquire().await.map_err(|e| {
+                Error::database_with_source("Failed to acquire a database connection", e)
+            })?;
+            return Ok(TxConnection::Autocommit(Box::new(conn)));
+        }
+
+        let mut state = self.state.lock().await;
+        match *state {
+            TxState::Idle => {
+                let tx =
+                    self.pool.begin().await.map_err(|e| {
+                        Error::database_with_source("Failed to begin transaction", e)
+                    })?;
+                *state = TxState::Open(Box::new(tx));
+            }
+            TxState::Open(_) => {}
+            TxState::Finished => {
+                return Err(Error::internal(
+                    "Request transaction used after the response was sent",
+                ));
+            }
+        }
+        Ok(TxConnection::Transaction(MutexGuard::map(
+            state,
+            |state| match state {
+                TxState::Open(tx) => &mut ***tx,
+                _ => unreachable!("transaction was just opened"),
+            },
+        )))
+    }
+
+    /// Commit or roll back whatever the request wrote
+    async fn finish(&self, commit: bool) -> Result<()> {
+        let state = std::mem::replace(&mut *self.state.lock().await, TxState::Finished);
+        let TxState::Open(tx) = state else {
+            return Ok(());
+        };
+        if commit {
+            tx.commit()
+                .await
+                .map_err(|e| Error::database_with_source("Failed to commit transaction", e))
+        } else {
+            tx.rollback()
+                .await
+                .map_err(|e| Error::database_with_source("Failed to rollback transaction", e))
+        }
+    }
+}
+
+/// A connection inside the request transaction, or an autocommit one
+pub enum TxConnection<'a> {
+    Transaction(MappedMutexGuard<'a, PgConnection>),
+    Autocommit(Box<PoolConnection<Postgres>>),
+}
+
+impl Deref for TxConnection<'_> {
+    type Target = PgConnection;
+
+    fn deref(&self) -> &PgConnection {
+        match self {
+            Self::Transaction(conn) => conn,
+            Self::Autocommit(conn) => conn,
+        }
+    }
+}
+
+impl DerefMut for TxConnection<'_> {
+    fn deref_mut(&mut self) -> &mut PgConnection {
+        match self {
+            Self::Transaction(conn) => conn,
+            Self::Autocommit(conn) => conn,
+        }
+    }
+}
+
+/// The request's transaction, for handlers
+///
+/// ```ignore
+/// async fn handler(tx: RequestTx) -> HttpResult<()> {
+///     let mut conn = tx.conn().await?;
+///     sqlx::query("...").execute(&mut *conn).await?;
+/// }
+/// ```
+#[derive(Clone)]
+pub struct RequestTx(pub Arc<RequestTransaction>);
+
+impl Deref for RequestTx {
+    type Target = RequestTransaction;
+
+    fn deref(&self) -> &RequestTransaction {
+        &self.0
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for RequestTx
+where
+    AppState: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = HttpError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &S,
+    ) -> std::result::Result<Self, Self::Rejection> {
+        if let Some(tx) = parts.extensions.get::<Arc<RequestTransaction>>() {
+            return Ok(Self(tx.clone()));
+        }
+        // Not covered by the middleware: nobody would commit, so fall back
+        // to autocommit
+        let app_state = AppState::from_ref(state);
+        Ok(Self(Arc::new(RequestTransaction::new(
+            app_state.db().inner().clone(),
+            true,
+        ))))
+    }
+}
+
+fn is_mutating(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::POST | Method::PUT | Method::PATCH | Method::DELETE
+    )
+}
+
+/// Wrap mutating requests in a transaction, committed on 2xx and rolled
+/// back otherwise
+pub async fn request_transaction(
+    State(state): State<AppState>,
+    mut request: Request<Body>,
+    next: Next,
+) -> Response {
+    if !is_mutating(request.method()) {
+        return next.run(request).await;
+    }
+
+    let tx = Arc::new(RequestTransaction::new(state.db().inner().clone(), false));
+    request.extensions_mut().insert(tx.clone());
+    let response = next.run(request).await;
+
+    let success = response.status().is_success();
+    match tx.finish(success).await {
+        Ok(()) => response,
+        // The handler reported success for writes that were then lost
+        Err(e) if success => HttpError::from(e).into_response(),
+        Err(e) => {
+            tracing::warn!("{}", e);
+            response
+        }
+    }
+}
+
+/// Route layer for long-running operations: statements commit one by one
+/// instead of holding a transaction open for the whole request
+pub async fn without_request_transaction(request: Request<Body>, next: Next) -> Response {
+    if let Some(tx) = request.extensions().get::<Arc<RequestTransaction>>() {
+        tx.autocommit.store(true, Ordering::SeqCst);
+    }
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        http::StatusCode,
+        middleware as axum_middleware,
+        routing::{get, post},
+        Router,
+    };
+    use rustpress_core::config::AppConfig;
+    use tower::ServiceExt;
+
+    fn mode(tx: Option<&Arc<RequestTransaction>>) -> &'static str {
+        match tx {
+            None => "none",
+            Some(tx) if tx.is_autocommit() => "autocommit",
+            Some(_) => "transaction",
+        }
+    }
+
+    async fn report(request: Request<Body>) -> String {
+        mode(request.extensions().get::<Arc<RequestTransaction>>()).to_string()
+    }
+
+    fn router() -> Router {
+        let state = AppState::for_tests(AppConfig::default());
+        Router::new()
+            .route("/write", get(report).post(report))
+            .route(
+                "/import",
+                post(report).route_layer(axum_middleware::from_fn(without_request_transaction)),
+            )
+            .layer(axum_middleware::from_fn_with_state(
+                state,
+                request_transaction,
+            ))
+    }
+
+    async fn call(method: Method, uri: &str) -> (StatusCode, String) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .body(Body::empty())
+            .unwrap();
+        let response = router().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_only_mutating_requests_get_a_transaction() {
+        assert_eq!(call(Method::GET, "/write").await.1, "none");
+        // The handler never used it, so there is nothing to commit
+        assert_eq!(
+            call(Method::POST, "/write").await,
+            (StatusCode::OK, "transaction".to_string())
+        );
+        assert_eq!(call(Method::POST, "/import").await.1, "autocommit");
+    }
+}
@@ -0,0 +1,183 @@
+//! WebAssembly plugins installed at runtime.
+//!
+//! Administrators upload a plugin's manifest and compiled module; the
+//! [`WasmPluginHost`] checks the module against the host ABI before the
+//! plugin is registered. The host serves the whole process, so on the
+//! sites of a network only network administrators manage it. Plugins found
+//! in the plugins directory are installed at startup.
+//!
+//! [`WasmPluginHost`]: rustpress_plugins::WasmPluginHost
+
+use std::path::Path;
+
+use axum::{
+    extract::State,
+    response::IntoResponse,
+    routing::{delete, get},
+    Json, Router,
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use rustpress_core::tenant::current_site;
+use rustpress_plugins::discovery::{DiscoveryConfig, PluginDiscovery};
+use rustpress_plugins::manifest::PluginType;
+use rustpress_plugins::{PluginManifest, WasmPluginHost};
+use serde::Deserialize;
+use tracing::{info, warn};
+
+use crate::error::{HttpError, HttpResult};
+use crate::extract::AuthUser;
+use crate::response::{created, json, no_content};
+use crate::services::sites::NETWORK_ADMIN_ROLE;
+use crate::state::AppState;
+
+/// A plugin to install
+#[derive(Debug, Deserialize)]
+pub struct InstallWasmPlugin {
+    /// Contents of the plugin's `plugin.toml`
+    pub manifest: String,
+    /// The compiled module, base64-encoded
+    pub module: String,
+}
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_handler).post(install_handler))
+        .route("/:id", delete(uninstall_handler))
+}
+
+/// Install the WebAssembly plugins in `plugins_dir`, those whose module
+/// is in place. Failures are logged and skip the plugin
+pub fn install_discovered(host: &WasmPluginHost, plugins_dir: &Path) {
+    let discovery = PluginDiscovery::new(DiscoveryConfig {
+        plugin_dirs: vec![plugins_dir.to_path_buf()],
+        must_use_dir: None,
+        dropin_dir: None,
+        ..Default::default()
+    });
+    let discovered = match discovery.scan_all() {
+        Ok(discovered) => discovered,
+        Err(e) => {
+            warn!("Failed to scan for WASM plugins: {}", e);
+            return;
+        }
+    };
+
+    // Manifests default to WASM, so native plugins sharing the directory
+    // are told apart by their missing module
+    for plugin in discovered.iter().filter(|p| {
+        p.manifest.plugin.plugin_type == PluginType::Wasm
+            && p.path.join(&p.manifest.plugin.entry).is_file()
+    }) {
+        match host.install_discovered(plugin) {
+            Ok(()) => info!(plugin_id = %plugin.manifest.plugin.id, "WASM plugin installed"),
+            Err(e) => warn!(
+                plugin_id = %plugin.manifest.plugin.id,
+                "Failed to install WASM plugin: {}", e
+            ),
+        }
+    }
+}
+
+fn require_host_admin(user: &AuthUser) -> HttpResult<()> {
+    let allowed = if current_site().is_some() {
+        user.has_role(NETWORK_ADMIN_ROLE)
+    } else {
+        user.is_admin()
+    };
+    if allowed {
+        Ok(())
+    } else {
+        Err(HttpError::forbidden(
+            "Only administrators can manage WebAssembly plugins",
+        ))
+    }
+}
+
+async fn list_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl IntoResponse> {
+    require_host_admin(&user)?;
+    Ok(json(serde_json::json!({
+        "plugins": state.wasm_plugins.installed()
+    })))
+}
+
+async fn install_handler(
+    user: AuthUser,
+    State(state): State<AppState>,
+    Json(payload): Json<InstallWasmPlugin>,
+) -> HttpResult<impl IntoResponse> {
+    require_host_admin(&user)?;
+
+    let manifest = PluginManifest::from_toml(&payload.manifest)
+        .map_err(|e| HttpError::unprocessable_entity(format!("Invalid manifest: {}", e)))?;
+    let module = BASE64
+        .decode(payload.module.trim())
+        .map_err(|_| HttpError::bad_request("The module is not valid base64"))?;
+    let plugin_id = manifest.plugin.id.clone();
+
+    // Compiling a module is CPU-bound
+    let host = state.wasm_plugins.clone();
+    tokio::task::spawn_blocking(move || host.install(&manifest, &module))
+        .await
+        .map_err(|e| HttpError::internal_error(format!("Install failed: {}", e)))?
+        .map_err(|e| HttpError::unprocessable_entity(e.to_string()))?;
+
+    info!(user_id = %user.id, plugin_id = %plugin_id, "WASM plugin installed");
+    let installed = state
+        .wasm_plugins
+        .installed()
+        .into_iter()
+        .find(|p| p.id == plugin_id);
+    Ok(created(installed))
+}
+
+async fn uninstall_handler(
+    user: AuthUser,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    State(state): State<AppState>,
+) -> HttpResult<impl IntoResponse> {
+    require_host_admin(&user)?;
+    if !state.wasm_plugins.uninstall(&id) {
+        return Err(HttpError::not_found(format!(
+            "WASM plugin '{}' is not installed",
+            id
+        )));
+    }
+    info!(user_id = %user.id, plugin_id = %id, "WASM plugin uninstalled");
+    Ok(no_content())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_install_discovered_skips_plugins_without_a_valid_module() {
+        let dir = std::env::temp_dir().join(format!("rustpress-wasm-{}", uuid::Uuid::new_v4()));
+        for (id, module) in [("native", None), ("broken", Some(&b"not wasm"[..]))] {
+            let plugin_dir = dir.join(id);
+            std::fs::create_dir_all(&plugin_dir).unwrap();
+            std::fs::write(
+                plugin_dir.join("plugin.toml"),
+                format!("[plugin]\nid = \"{id}\"\nname = \"{id}\"\nversion = \"1.0.0\"\n"),
+            )
+            .unwrap();
+            if let Some(module) = module {
+                std::fs::write(plugin_dir.join("plugin.wasm"), module).unwrap();
+            }
+        }
+
+        let host = WasmPluginHost::new(
+            Arc::new(rustpress_plugins::HookRegistry::new()),
+            Arc::new(rustpress_plugins::api::ApiRegistry::new()),
+            Arc::new(rustpress_plugins::SettingsManager::new()),
+        )
+        .unwrap();
+        install_discovered(&host, &dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(host.installed().is_empty());
+    }
+}
@@ -404,7 +404,7 @@ impl Default for WebSocketHub {
 }
 
 /// Generate a consistent color for a user based on their ID
-fn generate_user_color(user_id: &Uuid) -> String {
+pub(crate) fn generate_user_color(user_id: &Uuid) -> String {
     let colors = [
         "#FF6B6B", // Red
         "#4ECDC4", // Teal
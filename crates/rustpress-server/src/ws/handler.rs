@@ -0,0 +1,258 @@
+//! Live notification socket handler.
+//!
+//! The token is sent in the first frame instead of the query string so it
+//! never ends up in proxy or access logs:
+//!
+//! ```text
+//! -> {"type":"auth","token":"<access token>"}
+//! <- {"type":"ready","connection_id":"...","user_id":"...","tenant_id":null}
+//! -> {"type":"subscribe","channels":["comment.*","job.completed"]}
+//! <- {"type":"subscribed","channels":["comment.*","job.completed"]}
+//! <- {"type":"event","channel":"comment.*","event":{...}}
+//! ```
+
+use std::borrow::Cow;
+use std::time::Duration;
+
+use axum::{
+    extract::{
+        ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade},
+        State,
+    },
+    response::IntoResponse,
+    routing::get,
+    Router,
+};
+use futures::{stream::SplitStream, SinkExt, StreamExt};
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use super::manager::CONNECTION_BUFFER;
+use super::message::{ClientMessage, ServerMessage};
+use crate::error::{HttpError, HttpResult};
+use crate::response::json;
+use crate::state::AppState;
+
+/// Time allowed between upgrade and the `auth` message
+const AUTH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Interval between server pings on an idle connection
+const PING_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Roles allowed to open a dashboard notification stream
+const DASHBOARD_ROLES: &[&str] = &["administrator", "editor"];
+
+const CLOSE_UNAUTHORIZED: u16 = 4401;
+const CLOSE_FORBIDDEN: u16 = 4403;
+const CLOSE_AUTH_TIMEOUT: u16 = 4408;
+
+/// Live notification routes, mounted under `/api/v1/live`
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(live_socket_handler))
+        .route("/stats", get(live_stats_handler))
+}
+
+/// WebSocket upgrade handler
+pub async fn live_socket_handler(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    ws.on_upgrade(move |socket| handle_socket(socket, state))
+}
+
+/// Connection counters for the live notification stream
+async fn live_stats_handler(
+    user: crate::extract::AuthUser,
+    State(state): State<AppState>,
+) -> HttpResult<impl IntoResponse> {
+    if !user.is_admin() {
+        return Err(HttpError::forbidden(
+            "Only administrators can view live connection stats",
+        ));
+    }
+
+    Ok(json(LiveStatsResponse {
+        live: state.live.stats().await,
+        change_feed: state.change_feed.stats(),
+    }))
+}
+
+/// Live connection counters with the change feed feeding them
+#[derive(serde::Serialize)]
+struct LiveStatsResponse {
+    #[serde(flatten)]
+    live: super::LiveStats,
+    change_feed: crate::services::ChangeFeedStats,
+}
+
+/// Identity established by the auth handshake
+struct Handshake {
+    user_id: Uuid,
+    tenant_id: Option<Uuid>,
+}
+
+async fn handle_socket(socket: WebSocket, state: AppState) {
+    let (mut sender, mut receiver) = socket.split();
+
+    let handshake =
+        match tokio::time::timeout(AUTH_TIMEOUT, authenticate(&mut receiver, &state)).await {
+            Ok(Ok(handshake)) => handshake,
+            Ok(Err((code, reason))) => {
+                let _ = sender.send(close(code, reason)).await;
+                return;
+            }
+            Err(_) => {
+                let _ = sender
+                    .send(close(CLOSE_AUTH_TIMEOUT, "Authentication timed out"))
+                    .await;
+                return;
+            }
+        };
+
+    let connection_id = Uuid::new_v4();
+    let manager = state.live.clone();
+    let (tx, mut rx) = mpsc::channel::<ServerMessage>(CONNECTION_BUFFER);
+    manager
+        .register(
+            connection_id,
+            handshake.user_id,
+            handshake.tenant_id,
+            tx.clone(),
+        )
+        .await;
+
+    info!(
+        user = %handshake.user_id,
+        connection = %connection_id,
+        "Live notification socket connected"
+    );
+
+    let _ = tx
+        .send(ServerMessage::Ready {
+            connection_id,
+            user_id: handshake.user_id,
+            tenant_id: handshake.tenant_id,
+        })
+        .await;
+
+    // Outgoing: queued messages plus periodic pings
+    let mut send_task = tokio::spawn(async move {
+        let mut ping = tokio::time::interval(PING_INTERVAL);
+        ping.tick().await;
+        loop {
+            tokio::select! {
+                message = rx.recv() => {
+                    let Some(message) = message else { break };
+                    let Ok(text) = serde_json::to_string(&message) else { continue };
+                    if sender.send(Message::Text(text)).await.is_err() {
+                        break;
+                    }
+                }
+                _ = ping.tick() => {
+                    if sender.send(Message::Ping(Vec::new())).await.is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+
+    // Incoming: subscription management
+    let recv_manager = manager.clone();
+    let mut recv_task = tokio::spawn(async move {
+        while let Some(Ok(message)) = receiver.next().await {
+            let text = match message {
+                Message::Text(text) => text,
+                Message::Close(_) => break,
+                _ => continue,
+            };
+
+            let reply = match serde_json::from_str::<ClientMessage>(&text) {
+                Ok(ClientMessage::Subscribe { channels }) => {
+                    match recv_manager.subscribe(connection_id, &channels).await {
+                        Ok(channels) => ServerMessage::Subscribed { channels },
+                        Err(message) => ServerMessage::error("invalid_subscription", message),
+                    }
+                }
+                Ok(ClientMessage::Unsubscribe { channels }) => ServerMessage::Unsubscribed {
+                    channels: recv_manager.unsubscribe(connection_id, &channels).await,
+                },
+                Ok(ClientMessage::Ping) => ServerMessage::Pong,
+                Ok(ClientMessage::Auth { .. }) => ServerMessage::error(
+                    "already_authenticated",
+                    "Connection is already authenticated",
+                ),
+                Err(e) => {
+                    debug!("Invalid live socket message: {}", e);
+                    ServerMessage::error("invalid_message", "Failed to parse message")
+                }
+            };
+
+            if tx.send(reply).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    tokio::select! {
+        _ = &mut send_task => recv_task.abort(),
+        _ = &mut recv_task => send_task.abort(),
+    }
+
+    manager.unregister(connection_id).await;
+    info!(
+        user = %handshake.user_id,
+        connection = %connection_id,
+        "Live notification socket disconnected"
+    );
+}
+
+/// Wait for the `auth` message and validate its token
+async fn authenticate(
+    receiver: &mut SplitStream<WebSocket>,
+    state: &AppState,
+) -> Result<Handshake, (u16, &'static str)> {
+    let token = loop {
+        match receiver.next().await {
+            Some(Ok(Message::Text(text))) => match serde_json::from_str::<ClientMessage>(&text) {
+                Ok(ClientMessage::Auth { token }) => break token,
+                _ => return Err((CLOSE_UNAUTHORIZED, "Expected auth message")),
+            },
+            Some(Ok(Message::Ping(_) | Message::Pong(_))) => continue,
+            _ => return Err((CLOSE_UNAUTHORIZED, "Expected auth message")),
+        }
+    };
+
+    let claims = state.jwt.validate_access_token(&token).map_err(|e| {
+        warn!("Invalid live socket token: {}", e);
+        (CLOSE_UNAUTHORIZED, "Invalid or expired token")
+    })?;
+    let user_id = Uuid::parse_str(&claims.sub)
+        .map_err(|_| (CLOSE_UNAUTHORIZED, "Invalid user ID in token"))?;
+
+    if !claims
+        .role
+        .iter()
+        .any(|role| DASHBOARD_ROLES.contains(&role.as_str()))
+    {
+        return Err((CLOSE_FORBIDDEN, "Dashboard access required"));
+    }
+
+    let tenant_id = match claims.tenant_id.as_deref() {
+        Some(tenant) => Some(
+            Uuid::parse_str(tenant).map_err(|_| (CLOSE_UNAUTHORIZED, "Invalid tenant in token"))?,
+        ),
+        None => None,
+    };
+
+    Ok(Handshake { user_id, tenant_id })
+}
+
+fn close(code: u16, reason: &'static str) -> Message {
+    Message::Close(Some(CloseFrame {
+        code,
+        reason: Cow::Borrowed(reason),
+    }))
+}
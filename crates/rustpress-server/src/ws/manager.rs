@@ -0,0 +1,426 @@
+//! Connection manager for live dashboard notifications.
+//!
+//! Every connection belongs to a tenant (or to the global scope when the
+//! token carries no tenant) and subscribes to channels named after event
+//! types. A channel is either an exact event type (`comment.created`), a
+//! prefix wildcard (`job.*`) or `*` for everything. Events addressed to
+//! particular users, such as group notifications, carry their ids under
+//! [`RECIPIENTS_METADATA`] and only reach those users' connections.
+
+use std::collections::{BTreeSet, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use rustpress_events::{DomainEvent, EventBus};
+use serde::Serialize;
+use tokio::sync::{broadcast, mpsc, RwLock};
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+use super::message::{LiveEvent, ServerMessage};
+
+/// Outgoing messages buffered per connection before events are dropped
+pub const CONNECTION_BUFFER: usize = 256;
+
+/// Maximum channels a single connection may subscribe to
+pub const MAX_SUBSCRIPTIONS: usize = 64;
+
+/// Event metadata key listing the only users an event is delivered to
+pub const RECIPIENTS_METADATA: &str = "recipients";
+
+/// Message sender for a single connection
+pub type LiveSender = mpsc::Sender<ServerMessage>;
+
+/// A single authenticated connection
+#[derive(Debug)]
+struct LiveConnection {
+    user_id: Uuid,
+    tenant_id: Option<Uuid>,
+    channels: BTreeSet<String>,
+    sender: LiveSender,
+}
+
+impl LiveConnection {
+    /// Whether this connection may see an event from the given tenant
+    fn in_scope(&self, tenant_id: Option<Uuid>) -> bool {
+        tenant_id.is_none() || tenant_id == self.tenant_id
+    }
+
+    /// The first subscribed channel matching the event type
+    fn matching_channel(&self, event_type: &str) -> Option<&String> {
+        self.channels
+            .iter()
+            .find(|channel| channel_matches(channel, event_type))
+    }
+}
+
+/// Whether a channel pattern matches an event type
+pub fn channel_matches(channel: &str, event_type: &str) -> bool {
+    if channel == "*" {
+        return true;
+    }
+    match channel.strip_suffix(".*") {
+        Some(prefix) => event_type
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.starts_with('.')),
+        None => channel == event_type,
+    }
+}
+
+/// Validate a channel name supplied by a client
+pub fn valid_channel(channel: &str) -> bool {
+    if channel == "*" {
+        return true;
+    }
+    let name = channel.strip_suffix(".*").unwrap_or(channel);
+    !name.is_empty()
+        && name.len() <= 100
+        && name.split('.').all(|part| {
+            !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        })
+}
+
+/// Snapshot of live connection counters
+#[derive(Debug, Clone, Serialize)]
+pub struct LiveStats {
+    pub connections: usize,
+    pub users: usize,
+    pub tenants: usize,
+    pub subscriptions: HashMap<String, usize>,
+    pub delivered: u64,
+    pub dropped: u64,
+}
+
+/// Tracks live connections and fans domain events out to subscribers
+#[derive(Debug, Default)]
+pub struct ConnectionManager {
+    connections: RwLock<HashMap<Uuid, LiveConnection>>,
+    delivered: AtomicU64,
+    dropped: AtomicU64,
+}
+
+impl ConnectionManager {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    /// Register an authenticated connection with no subscriptions
+    pub async fn register(
+        &self,
+        connection_id: Uuid,
+        user_id: Uuid,
+        tenant_id: Option<Uuid>,
+        sender: LiveSender,
+    ) {
+        self.connections.write().await.insert(
+            connection_id,
+            LiveConnection {
+                user_id,
+                tenant_id,
+                channels: BTreeSet::new(),
+                sender,
+            },
+        );
+    }
+
+    pub async fn unregister(&self, connection_id: Uuid) {
+        self.connections.write().await.remove(&connection_id);
+    }
+
+    /// Add subscriptions, returning the connection's full channel list
+    pub async fn subscribe(
+        &self,
+        connection_id: Uuid,
+        channels: &[String],
+    ) -> Result<Vec<String>, String> {
+        if let Some(bad) = channels.iter().find(|c| !valid_channel(c)) {
+            return Err(format!("Invalid channel '{}'", bad));
+        }
+
+        let mut connections = self.connections.write().await;
+        let connection = connections
+            .get_mut(&connection_id)
+            .ok_or_else(|| "Connection is not registered".to_string())?;
+
+        let added = channels
+            .iter()
+            .filter(|c| !connection.channels.contains(*c))
+            .count();
+        if connection.channels.len() + added > MAX_SUBSCRIPTIONS {
+            return Err(format!(
+                "At most {} channels may be subscribed",
+                MAX_SUBSCRIPTIONS
+            ));
+        }
+
+        connection.channels.extend(channels.iter().cloned());
+        Ok(connection.channels.iter().cloned().collect())
+    }
+
+    /// Remove subscriptions, returning the connection's remaining channels
+    pub async fn unsubscribe(&self, connection_id: Uuid, channels: &[String]) -> Vec<String> {
+        let mut connections = self.connections.write().await;
+        match connections.get_mut(&connection_id) {
+            Some(connection) => {
+                for channel in channels {
+                    connection.channels.remove(channel);
+                }
+                connection.channels.iter().cloned().collect()
+            }
+            None => Vec::new(),
+        }
+    }
+
+    /// Deliver an event to every in-scope connection subscribed to it.
+    ///
+    /// Tenant events only reach connections of the same tenant; events
+    /// without a tenant are global. Events listing recipients only reach
+    /// those users. Slow consumers whose buffer is full miss the event
+    /// rather than stalling the fan-out.
+    pub async fn dispatch(&self, event: &DomainEvent) -> usize {
+        let recipients: Option<Vec<Uuid>> = event
+            .metadata
+            .data
+            .get(RECIPIENTS_METADATA)
+            .map(|v| serde_json::from_value(v.clone()).unwrap_or_default());
+        let connections = self.connections.read().await;
+        let mut live_event: Option<LiveEvent> = None;
+        let mut sent = 0;
+
+        for (id, connection) in connections.iter() {
+            if !connection.in_scope(event.tenant_id) {
+                continue;
+            }
+            if recipients
+                .as_ref()
+                .is_some_and(|r| !r.contains(&connection.user_id))
+            {
+                continue;
+            }
+            let Some(channel) = connection.matching_channel(&event.event_type) else {
+                continue;
+            };
+
+            let message = ServerMessage::Event {
+                channel: channel.clone(),
+                event: live_event
+                    .get_or_insert_with(|| LiveEvent::from(event))
+                    .clone(),
+            };
+            match connection.sender.try_send(message) {
+                Ok(()) => sent += 1,
+                Err(mpsc::error::TrySendError::Full(_)) => {
+                    self.dropped.fetch_add(1, Ordering::Relaxed);
+                    debug!(connection = %id, "Live connection buffer full, dropping event");
+                }
+                Err(mpsc::error::TrySendError::Closed(_)) => {}
+            }
+        }
+
+        self.delivered.fetch_add(sent as u64, Ordering::Relaxed);
+        sent
+    }
+
+    /// Forward every event published on the bus to subscribed connections
+    pub fn spawn_event_bridge(self: &Arc<Self>, bus: &EventBus) -> JoinHandle<()> {
+        let manager = self.clone();
+        let mut rx = bus.subscribe_broadcast();
+        tokio::spawn(async move {
+            loop {
+                match rx.recv().await {
+                    Ok(event) => {
+                        manager.dispatch(&event).await;
+                    }
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!(
+                            skipped,
+                            "Live notification bridge lagged behind the event bus"
+                        );
+                        manager.dropped.fetch_add(skipped, Ordering::Relaxed);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        })
+    }
+
+    pub async fn stats(&self) -> LiveStats {
+        let connections = self.connections.read().await;
+        let mut subscriptions: HashMap<String, usize> = HashMap::new();
+        for connection in connections.values() {
+            for channel in &connection.channels {
+                *subscriptions.entry(channel.clone()).or_default() += 1;
+            }
+        }
+
+        LiveStats {
+            connections: connections.len(),
+            users: connections
+                .values()
+                .map(|c| c.user_id)
+                .collect::<BTreeSet<_>>()
+                .len(),
+            tenants: connections
+                .values()
+                .filter_map(|c| c.tenant_id)
+                .collect::<BTreeSet<_>>()
+                .len(),
+            subscriptions,
+            delivered: self.delivered.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_channel_patterns() {
+        assert!(channel_matches("*", "comment.created"));
+        assert!(channel_matches("comment.created", "comment.created"));
+        assert!(channel_matches("comment.*", "comment.created"));
+        assert!(!channel_matches("comment.*", "commentary.created"));
+        assert!(!channel_matches("comment.*", "comment"));
+        assert!(!channel_matches("job.completed", "job.failed"));
+
+        assert!(valid_channel("job.*"));
+        assert!(valid_channel("analytics.pulse"));
+        assert!(!valid_channel("job.**"));
+        assert!(!valid_channel(".*"));
+        assert!(!valid_channel("comment..created"));
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_respects_tenants_and_subscriptions() {
+        let manager = ConnectionManager::new();
+        let tenant_a = Uuid::new_v4();
+        let tenant_b = Uuid::new_v4();
+
+        let (tx_a, mut rx_a) = mpsc::channel(CONNECTION_BUFFER);
+        let (tx_b, mut rx_b) = mpsc::channel(CONNECTION_BUFFER);
+        let conn_a = Uuid::new_v4();
+        let conn_b = Uuid::new_v4();
+        manager
+            .register(conn_a, Uuid::new_v4(), Some(tenant_a), tx_a)
+            .await;
+        manager
+            .register(conn_b, Uuid::new_v4(), Some(tenant_b), tx_b)
+            .await;
+        manager
+            .subscribe(conn_a, &["comment.*".to_string()])
+            .await
+            .unwrap();
+        manager.subscribe(conn_b, &["*".to_string()]).await.unwrap();
+
+        let comment =
+            DomainEvent::new("comment.created", serde_json::json!({})).with_tenant(tenant_a);
+        assert_eq!(manager.dispatch(&comment).await, 1);
+        assert!(matches!(
+            rx_a.try_recv(),
+            Ok(ServerMessage::Event { ref channel, .. }) if channel == "comment.*"
+        ));
+        assert!(rx_b.try_recv().is_err());
+
+        let global = DomainEvent::new("job.completed", serde_json::json!({}));
+        assert_eq!(manager.dispatch(&global).await, 1);
+        assert!(rx_a.try_recv().is_err());
+        assert!(rx_b.try_recv().is_ok());
+
+        manager.unregister(conn_b).await;
+        let stats = manager.stats().await;
+        assert_eq!(stats.connections, 1);
+        assert_eq!(stats.delivered, 2);
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_to_recipients() {
+        let manager = ConnectionManager::new();
+        let member = Uuid::new_v4();
+
+        let (tx_member, mut rx_member) = mpsc::channel(CONNECTION_BUFFER);
+        let (tx_other, mut rx_other) = mpsc::channel(CONNECTION_BUFFER);
+        let conn_member = Uuid::new_v4();
+        let conn_other = Uuid::new_v4();
+        manager.register(conn_member, member, None, tx_member).await;
+        manager
+            .register(conn_other, Uuid::new_v4(), None, tx_other)
+            .await;
+        for conn in [conn_member, conn_other] {
+            manager.subscribe(conn, &["*".to_string()]).await.unwrap();
+        }
+
+        let notification = DomainEvent::new("group.notification", serde_json::json!({}))
+            .with_metadata(RECIPIENTS_METADATA, serde_json::json!([member]));
+        assert_eq!(manager.dispatch(&notification).await, 1);
+        assert!(rx_member.try_recv().is_ok());
+        assert!(rx_other.try_recv().is_err());
+
+        let malformed = DomainEvent::new("group.notification", serde_json::json!({}))
+            .with_metadata(RECIPIENTS_METADATA, serde_json::json!("everyone"));
+        assert_eq!(manager.dispatch(&malformed).await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_subscription_limits() {
+        let manager = ConnectionManager::new();
+        let (tx, _rx) = mpsc::channel(1);
+        let id = Uuid::new_v4();
+        manager.register(id, Uuid::new_v4(), None, tx).await;
+
+        assert!(manager
+            .subscribe(id, &["bad channel".to_string()])
+            .await
+            .is_err());
+        let many: Vec<String> = (0..=MAX_SUBSCRIPTIONS).map(|i| format!("e{}", i)).collect();
+        assert!(manager.subscribe(id, &many).await.is_err());
+
+        let channels = manager
+            .subscribe(id, &["job.*".to_string(), "job.*".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(channels, vec!["job.*".to_string()]);
+        assert!(manager.unsubscribe(id, &channels).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_published_events_reach_subscribed_sockets() {
+        let bus = EventBus::new();
+        let manager = ConnectionManager::new();
+        let bridge = manager.spawn_event_bridge(&bus);
+
+        let (tx, mut rx) = mpsc::channel(CONNECTION_BUFFER);
+        let id = Uuid::new_v4();
+        manager.register(id, Uuid::new_v4(), None, tx).await;
+        manager
+            .subscribe(id, &["analytics.pulse".to_string()])
+            .await
+            .unwrap();
+
+        bus.publish(DomainEvent::new("comment.created", serde_json::json!({})))
+            .await
+            .unwrap();
+        bus.publish(DomainEvent::new(
+            "analytics.pulse",
+            serde_json::json!({"page_views": 3}),
+        ))
+        .await
+        .unwrap();
+
+        let message = tokio::time::timeout(std::time::Duration::from_secs(1), rx.recv())
+            .await
+            .expect("event was not bridged")
+            .unwrap();
+        match message {
+            ServerMessage::Event { channel, event } => {
+                assert_eq!(channel, "analytics.pulse");
+                assert_eq!(event.event_type, "analytics.pulse");
+            }
+            other => panic!("unexpected message: {:?}", other),
+        }
+        assert!(rx.try_recv().is_err());
+        bridge.abort();
+    }
+}
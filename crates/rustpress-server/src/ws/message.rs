@@ -0,0 +1,81 @@
+//! Wire protocol for the live notification socket.
+
+use chrono::{DateTime, Utc};
+use rustpress_events::DomainEvent;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Messages sent by the dashboard to the server
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    /// Handshake; must be the first message on a new connection
+    Auth { token: String },
+    /// Start receiving events matching the given channels
+    Subscribe { channels: Vec<String> },
+    /// Stop receiving events for the given channels
+    Unsubscribe { channels: Vec<String> },
+    /// Application-level keepalive
+    Ping,
+}
+
+/// Messages sent by the server to the dashboard
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    /// Handshake accepted
+    Ready {
+        connection_id: Uuid,
+        user_id: Uuid,
+        tenant_id: Option<Uuid>,
+    },
+    /// Current subscriptions after a subscribe request
+    Subscribed { channels: Vec<String> },
+    /// Current subscriptions after an unsubscribe request
+    Unsubscribed { channels: Vec<String> },
+    /// A domain event matching one of the subscriptions
+    Event { channel: String, event: LiveEvent },
+    /// Reply to a client ping
+    Pong,
+    /// Protocol or authorization error
+    Error { code: String, message: String },
+}
+
+impl ServerMessage {
+    pub fn error(code: &str, message: impl Into<String>) -> Self {
+        Self::Error {
+            code: code.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Client-facing view of a domain event.
+///
+/// Metadata such as correlation IDs and the originating IP stays on the
+/// server; only the event identity and payload are forwarded.
+#[derive(Debug, Clone, Serialize)]
+pub struct LiveEvent {
+    pub id: Uuid,
+    #[serde(rename = "type")]
+    pub event_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aggregate_id: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aggregate_type: Option<String>,
+    pub payload: serde_json::Value,
+    pub occurred_at: DateTime<Utc>,
+}
+
+impl From<&DomainEvent> for LiveEvent {
+    fn from(event: &DomainEvent) -> Self {
+        Self {
+            id: event.id,
+            event_type: event.event_type.clone(),
+            aggregate_id: event.aggregate_id,
+            aggregate_type: event.aggregate_type.clone(),
+            payload: event.payload.clone(),
+            occurred_at: event.occurred_at,
+        }
+    }
+}
@@ -0,0 +1,14 @@
+//! Live notifications for the admin dashboard.
+//!
+//! This module provides:
+//! - An authenticated WebSocket endpoint with a token handshake
+//! - Per-tenant connection tracking and channel subscriptions
+//! - A bridge that streams `EventBus` domain events to subscribers
+
+pub mod handler;
+pub mod manager;
+pub mod message;
+
+pub use handler::{live_socket_handler, routes};
+pub use manager::{ConnectionManager, LiveStats, RECIPIENTS_METADATA};
+pub use message::{ClientMessage, LiveEvent, ServerMessage};
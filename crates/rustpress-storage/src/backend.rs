@@ -1,10 +1,14 @@
 //! Storage backend implementations.
 
 use crate::file::{PathGenerator, StoredFile, UploadRequest};
+use crate::presign::PresignedUrl;
+use crate::upload::UploadedPart;
 use async_trait::async_trait;
 use bytes::Bytes;
 use rustpress_core::error::{Error, Result};
+use rustpress_core::fault::{FaultInjector, FaultTarget};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 /// Storage backend trait
 #[async_trait]
@@ -44,6 +48,69 @@ pub trait StorageBackend: Send + Sync {
 
     /// Health check
     async fn health_check(&self) -> Result<()>;
+
+    /// Whether clients can upload straight to the backend with presigned
+    /// URLs
+    fn supports_direct_upload(&self) -> bool {
+        false
+    }
+
+    /// Presigned URL to PUT the object at `path` in a single request
+    async fn presigned_put_url(&self, path: &str, _expires_in_secs: u64) -> Result<PresignedUrl> {
+        Err(unsupported(self.name(), "presigned uploads", path))
+    }
+
+    /// Start a multipart upload to `path`, returning its upload ID
+    async fn create_multipart(&self, path: &str) -> Result<String> {
+        Err(unsupported(self.name(), "multipart uploads", path))
+    }
+
+    /// Presigned URL to PUT one part of a multipart upload
+    async fn presigned_part_url(
+        &self,
+        path: &str,
+        _upload_id: &str,
+        _part_number: u32,
+        _expires_in_secs: u64,
+    ) -> Result<PresignedUrl> {
+        Err(unsupported(self.name(), "multipart uploads", path))
+    }
+
+    /// Upload one part of a multipart upload through the server
+    async fn upload_part(
+        &self,
+        path: &str,
+        _upload_id: &str,
+        _part_number: u32,
+        _content: Bytes,
+    ) -> Result<UploadedPart> {
+        Err(unsupported(self.name(), "multipart uploads", path))
+    }
+
+    /// Assemble the object from its parts, ordered by part number
+    async fn complete_multipart(
+        &self,
+        path: &str,
+        _upload_id: &str,
+        _parts: &[UploadedPart],
+    ) -> Result<()> {
+        Err(unsupported(self.name(), "multipart uploads", path))
+    }
+
+    /// Abort a multipart upload and drop its parts
+    async fn abort_multipart(&self, path: &str, _upload_id: &str) -> Result<()> {
+        Err(unsupported(self.name(), "multipart uploads", path))
+    }
+}
+
+fn unsupported(backend: &str, feature: &str, path: &str) -> Error {
+    Error::Storage {
+        message: format!(
+            "The {} backend does not support {} (path '{}')",
+            backend, feature, path
+        ),
+        source: None,
+    }
 }
 
 /// Local filesystem storage backend
@@ -301,10 +368,15 @@ impl StorageBackend for LocalBackend {
     }
 }
 
+/// Uploads above this size go to S3 in parts
+#[cfg(feature = "s3")]
+pub const S3_MULTIPART_THRESHOLD: u64 = 64 * 1024 * 1024;
+
 /// S3-compatible storage backend
 #[cfg(feature = "s3")]
 pub struct S3Backend {
     store: object_store::aws::AmazonS3,
+    presigner: crate::presign::S3Presigner,
     bucket: String,
     base_url: Option<String>,
 }
@@ -320,24 +392,36 @@ impl S3Backend {
         use object_store::aws::AmazonS3Builder;
 
         let bucket = bucket.into();
+        let region = region.into();
+        let access_key = access_key.into();
+        let secret_key = secret_key.into();
         let store = AmazonS3Builder::new()
             .with_bucket_name(&bucket)
-            .with_region(region)
-            .with_access_key_id(access_key)
-            .with_secret_access_key(secret_key)
+            .with_region(&region)
+            .with_access_key_id(&access_key)
+            .with_secret_access_key(&secret_key)
             .build()
             .map_err(|e| Error::Storage {
                 message: format!("Failed to create S3 backend: {}", e),
                 source: Some(Box::new(e)),
             })?;
+        let presigner = crate::presign::S3Presigner::new(
+            &format!("https://s3.{}.amazonaws.com/{}", region, bucket),
+            region,
+            access_key,
+            secret_key,
+        )?;
 
         Ok(Self {
             store,
+            presigner,
             bucket,
             base_url: None,
         })
     }
 
+    /// Backend for an S3-compatible service such as MinIO or R2, addressed
+    /// path-style under `endpoint`
     pub fn with_endpoint(
         bucket: impl Into<String>,
         endpoint: impl Into<String>,
@@ -347,20 +431,31 @@ impl S3Backend {
         use object_store::aws::AmazonS3Builder;
 
         let bucket = bucket.into();
+        let endpoint = endpoint.into();
+        let access_key = access_key.into();
+        let secret_key = secret_key.into();
         let store = AmazonS3Builder::new()
             .with_bucket_name(&bucket)
-            .with_endpoint(endpoint)
-            .with_access_key_id(access_key)
-            .with_secret_access_key(secret_key)
+            .with_endpoint(&endpoint)
+            .with_access_key_id(&access_key)
+            .with_secret_access_key(&secret_key)
             .with_allow_http(true)
             .build()
             .map_err(|e| Error::Storage {
                 message: format!("Failed to create S3 backend: {}", e),
                 source: Some(Box::new(e)),
             })?;
+        // object_store signs requests to custom endpoints for us-east-1
+        let presigner = crate::presign::S3Presigner::new(
+            &format!("{}/{}", endpoint.trim_end_matches('/'), bucket),
+            "us-east-1",
+            access_key,
+            secret_key,
+        )?;
 
         Ok(Self {
             store,
+            presigner,
             bucket,
             base_url: None,
         })
@@ -370,6 +465,34 @@ impl S3Backend {
         self.base_url = Some(url.into());
         self
     }
+
+    /// Bucket the backend stores objects in
+    pub fn bucket(&self) -> &str {
+        &self.bucket
+    }
+
+    /// Upload `content` in parts, aborting the upload if any part fails
+    async fn store_multipart(&self, path: &str, content: Bytes) -> Result<()> {
+        let upload_id = self.create_multipart(path).await?;
+        let part_size = crate::upload::part_size_for(content.len() as u64) as usize;
+
+        let mut parts = Vec::new();
+        for (idx, start) in (0..content.len()).step_by(part_size).enumerate() {
+            let end = (start + part_size).min(content.len());
+            let part = self
+                .upload_part(path, &upload_id, idx as u32 + 1, content.slice(start..end))
+                .await;
+            match part {
+                Ok(part) => parts.push(part),
+                Err(e) => {
+                    self.abort_multipart(path, &upload_id).await.ok();
+                    return Err(e);
+                }
+            }
+        }
+
+        self.complete_multipart(path, &upload_id, &parts).await
+    }
 }
 
 #[cfg(feature = "s3")]
@@ -383,18 +506,21 @@ impl StorageBackend for S3Backend {
         use object_store::ObjectStore;
 
         let path = PathGenerator::generate(&request.filename, request.directory.as_deref());
-        let location = object_store::path::Path::from(path.clone());
-
-        self.store
-            .put(&location, request.content.clone().into())
-            .await
-            .map_err(|e| Error::Storage {
-                message: format!("Failed to upload to S3: {}", e),
-                source: Some(Box::new(e)),
-            })?;
-
         let size = request.content.len() as u64;
 
+        if size > S3_MULTIPART_THRESHOLD {
+            self.store_multipart(&path, request.content.clone()).await?;
+        } else {
+            let location = object_store::path::Path::from(path.clone());
+            self.store
+                .put(&location, request.content.clone().into())
+                .await
+                .map_err(|e| Error::Storage {
+                    message: format!("Failed to upload to S3: {}", e),
+                    source: Some(Box::new(e)),
+                })?;
+        }
+
         let mut file = StoredFile::new(&path, &request.filename, &request.mime_type, size)
             .with_backend("s3")
             .with_metadata(request.metadata);
@@ -511,12 +637,11 @@ impl StorageBackend for S3Backend {
             .map(|base| format!("{}/{}", base.trim_end_matches('/'), path))
     }
 
-    async fn temporary_url(&self, path: &str, _expires_in_secs: u64) -> Result<String> {
-        // For a real implementation, you'd use pre-signed URLs
-        self.url(path).ok_or_else(|| Error::Storage {
-            message: "Base URL not configured".to_string(),
-            source: None,
-        })
+    async fn temporary_url(&self, path: &str, expires_in_secs: u64) -> Result<String> {
+        Ok(self
+            .presigner
+            .presign("GET", path, &[], expires_in_secs)?
+            .url)
     }
 
     async fn list(&self, prefix: &str) -> Result<Vec<String>> {
@@ -551,6 +676,253 @@ impl StorageBackend for S3Backend {
 
         Ok(())
     }
+
+    fn supports_direct_upload(&self) -> bool {
+        true
+    }
+
+    async fn presigned_put_url(&self, path: &str, expires_in_secs: u64) -> Result<PresignedUrl> {
+        self.presigner.presign("PUT", path, &[], expires_in_secs)
+    }
+
+    async fn create_multipart(&self, path: &str) -> Result<String> {
+        use object_store::multipart::MultiPartStore;
+
+        let location = object_store::path::Path::from(path);
+        self.store
+            .create_multipart(&location)
+            .await
+            .map_err(|e| Error::Storage {
+                message: format!("Failed to start S3 multipart upload: {}", e),
+                source: Some(Box::new(e)),
+            })
+    }
+
+    async fn presigned_part_url(
+        &self,
+        path: &str,
+        upload_id: &str,
+        part_number: u32,
+        expires_in_secs: u64,
+    ) -> Result<PresignedUrl> {
+        let part_number = part_number.to_string();
+        self.presigner.presign(
+            "PUT",
+            path,
+            &[("partNumber", &part_number), ("uploadId", upload_id)],
+            expires_in_secs,
+        )
+    }
+
+    async fn upload_part(
+        &self,
+        path: &str,
+        upload_id: &str,
+        part_number: u32,
+        content: Bytes,
+    ) -> Result<UploadedPart> {
+        use object_store::multipart::MultiPartStore;
+
+        if part_number == 0 {
+            return Err(Error::InvalidInput {
+                field: "part_number".to_string(),
+                message: "Part numbers start at 1".to_string(),
+            });
+        }
+
+        let location = object_store::path::Path::from(path);
+        let part = self
+            .store
+            .put_part(
+                &location,
+                &upload_id.to_string(),
+                part_number as usize - 1,
+                content,
+            )
+            .await
+            .map_err(|e| Error::Storage {
+                message: format!("Failed to upload S3 part {}: {}", part_number, e),
+                source: Some(Box::new(e)),
+            })?;
+
+        Ok(UploadedPart {
+            part_number,
+            etag: part.content_id,
+        })
+    }
+
+    async fn complete_multipart(
+        &self,
+        path: &str,
+        upload_id: &str,
+        parts: &[UploadedPart],
+    ) -> Result<()> {
+        use object_store::multipart::{MultiPartStore, PartId};
+
+        // S3 numbers parts by their position in the list
+        let mut parts = parts.to_vec();
+        parts.sort_by_key(|p| p.part_number);
+        let parts = parts
+            .into_iter()
+            .map(|p| PartId { content_id: p.etag })
+            .collect();
+
+        let location = object_store::path::Path::from(path);
+        self.store
+            .complete_multipart(&location, &upload_id.to_string(), parts)
+            .await
+            .map_err(|e| Error::Storage {
+                message: format!("Failed to complete S3 multipart upload: {}", e),
+                source: Some(Box::new(e)),
+            })?;
+
+        Ok(())
+    }
+
+    async fn abort_multipart(&self, path: &str, upload_id: &str) -> Result<()> {
+        use object_store::multipart::MultiPartStore;
+
+        let location = object_store::path::Path::from(path);
+        self.store
+            .abort_multipart(&location, &upload_id.to_string())
+            .await
+            .map_err(|e| Error::Storage {
+                message: format!("Failed to abort S3 multipart upload: {}", e),
+                source: Some(Box::new(e)),
+            })
+    }
+}
+
+/// Backend wrapper that injects configured storage faults before each call
+pub struct FaultInjectingBackend {
+    inner: Arc<dyn StorageBackend>,
+    faults: FaultInjector,
+}
+
+impl FaultInjectingBackend {
+    pub fn new(inner: Arc<dyn StorageBackend>, faults: FaultInjector) -> Self {
+        Self { inner, faults }
+    }
+
+    async fn inject(&self) -> Result<()> {
+        self.faults.inject(FaultTarget::Storage, None).await
+    }
+}
+
+#[async_trait]
+impl StorageBackend for FaultInjectingBackend {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    async fn store(&self, request: UploadRequest) -> Result<StoredFile> {
+        self.inject().await?;
+        self.inner.store(request).await
+    }
+
+    async fn get(&self, path: &str) -> Result<Bytes> {
+        self.inject().await?;
+        self.inner.get(path).await
+    }
+
+    async fn delete(&self, path: &str) -> Result<bool> {
+        self.inject().await?;
+        self.inner.delete(path).await
+    }
+
+    async fn exists(&self, path: &str) -> Result<bool> {
+        self.inject().await?;
+        self.inner.exists(path).await
+    }
+
+    async fn size(&self, path: &str) -> Result<u64> {
+        self.inject().await?;
+        self.inner.size(path).await
+    }
+
+    async fn copy(&self, from: &str, to: &str) -> Result<StoredFile> {
+        self.inject().await?;
+        self.inner.copy(from, to).await
+    }
+
+    async fn move_file(&self, from: &str, to: &str) -> Result<StoredFile> {
+        self.inject().await?;
+        self.inner.move_file(from, to).await
+    }
+
+    fn url(&self, path: &str) -> Option<String> {
+        self.inner.url(path)
+    }
+
+    async fn temporary_url(&self, path: &str, expires_in_secs: u64) -> Result<String> {
+        self.inject().await?;
+        self.inner.temporary_url(path, expires_in_secs).await
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        self.inject().await?;
+        self.inner.list(prefix).await
+    }
+
+    async fn health_check(&self) -> Result<()> {
+        self.inject().await?;
+        self.inner.health_check().await
+    }
+
+    fn supports_direct_upload(&self) -> bool {
+        self.inner.supports_direct_upload()
+    }
+
+    async fn presigned_put_url(&self, path: &str, expires_in_secs: u64) -> Result<PresignedUrl> {
+        self.inject().await?;
+        self.inner.presigned_put_url(path, expires_in_secs).await
+    }
+
+    async fn create_multipart(&self, path: &str) -> Result<String> {
+        self.inject().await?;
+        self.inner.create_multipart(path).await
+    }
+
+    async fn presigned_part_url(
+        &self,
+        path: &str,
+        upload_id: &str,
+        part_number: u32,
+        expires_in_secs: u64,
+    ) -> Result<PresignedUrl> {
+        self.inject().await?;
+        self.inner
+            .presigned_part_url(path, upload_id, part_number, expires_in_secs)
+            .await
+    }
+
+    async fn upload_part(
+        &self,
+        path: &str,
+        upload_id: &str,
+        part_number: u32,
+        content: Bytes,
+    ) -> Result<UploadedPart> {
+        self.inject().await?;
+        self.inner
+            .upload_part(path, upload_id, part_number, content)
+            .await
+    }
+
+    async fn complete_multipart(
+        &self,
+        path: &str,
+        upload_id: &str,
+        parts: &[UploadedPart],
+    ) -> Result<()> {
+        self.inject().await?;
+        self.inner.complete_multipart(path, upload_id, parts).await
+    }
+
+    async fn abort_multipart(&self, path: &str, upload_id: &str) -> Result<()> {
+        self.inject().await?;
+        self.inner.abort_multipart(path, upload_id).await
+    }
 }
 
 #[cfg(test)]
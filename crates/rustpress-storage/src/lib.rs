@@ -4,11 +4,15 @@
 
 pub mod backend;
 pub mod file;
+pub mod presign;
 pub mod storage;
+pub mod upload;
 
-pub use backend::{LocalBackend, StorageBackend};
+pub use backend::{FaultInjectingBackend, LocalBackend, StorageBackend};
 pub use file::{FileMetadata, StoredFile};
-pub use storage::{Storage, StorageConfig};
+pub use presign::{PresignedUrl, S3Presigner};
+pub use storage::{DirectUpload, Storage, StorageConfig};
+pub use upload::{PartUploadUrl, UploadSession, UploadedPart};
 
 #[cfg(feature = "s3")]
 pub use backend::S3Backend;
@@ -1,10 +1,20 @@
 //! High-level storage API.
 
 use crate::backend::StorageBackend;
-use crate::file::{FileMetadata, StoredFile, UploadRequest};
+use crate::file::{FileMetadata, PathGenerator, StoredFile, UploadRequest};
+use crate::presign::PresignedUrl;
+use crate::upload::{PartUploadUrl, UploadSession, UploadedPart};
 use bytes::Bytes;
+use parking_lot::RwLock;
 use rustpress_core::error::{Error, Result};
+use rustpress_core::tenant::{current_site, MeteredResource, UsageMeter};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::Arc;
+use uuid::Uuid;
+
+/// Largest object S3 accepts in a single PUT (5GB)
+pub const MAX_SINGLE_PUT_SIZE: u64 = 5 * 1024 * 1024 * 1024;
 
 /// Storage configuration
 #[derive(Debug, Clone)]
@@ -19,6 +29,10 @@ pub struct StorageConfig {
     pub default_directory: Option<String>,
     /// CDN URL for serving files
     pub cdn_url: Option<String>,
+    /// Maximum size in bytes of uploads going straight to the backend
+    pub max_direct_upload_size: u64,
+    /// Seconds an unfinished multipart upload session is kept
+    pub upload_session_ttl_secs: u64,
 }
 
 impl Default for StorageConfig {
@@ -45,28 +59,53 @@ impl Default for StorageConfig {
             ],
             default_directory: None,
             cdn_url: None,
+            max_direct_upload_size: 5 * 1024 * 1024 * 1024 * 1024, // 5TB
+            upload_session_ttl_secs: 24 * 60 * 60,
         }
     }
 }
 
+/// Presigned single-request upload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirectUpload {
+    /// Path the object is stored at
+    pub path: String,
+    #[serde(flatten)]
+    pub presigned: PresignedUrl,
+}
+
 /// High-level storage interface
 pub struct Storage {
     backend: Arc<dyn StorageBackend>,
     config: StorageConfig,
+    /// Multipart uploads in progress by session ID
+    sessions: RwLock<HashMap<Uuid, UploadSession>>,
+    /// Quotas and usage of the site being served
+    meter: Option<Arc<dyn UsageMeter>>,
 }
 
 impl Storage {
     /// Create new storage with backend
     pub fn new(backend: Arc<dyn StorageBackend>) -> Self {
+        Self::with_config(backend, StorageConfig::default())
+    }
+
+    /// Create storage with custom configuration
+    pub fn with_config(backend: Arc<dyn StorageBackend>, config: StorageConfig) -> Self {
         Self {
             backend,
-            config: StorageConfig::default(),
+            config,
+            sessions: RwLock::new(HashMap::new()),
+            meter: None,
         }
     }
 
-    /// Create storage with custom configuration
-    pub fn with_config(backend: Arc<dyn StorageBackend>, config: StorageConfig) -> Self {
-        Self { backend, config }
+    /// Check uploads against the storage and media quotas of the site
+    /// being served and record the files it keeps. Presigned single-PUT
+    /// uploads are checked but not recorded, as they bypass the server.
+    pub fn with_meter(mut self, meter: Arc<dyn UsageMeter>) -> Self {
+        self.meter = Some(meter);
+        self
     }
 
     /// Upload a file
@@ -83,7 +122,7 @@ impl Storage {
             request = request.with_directory(dir);
         }
 
-        self.backend.store(request).await
+        self.store(request).await
     }
 
     /// Upload a file with metadata
@@ -101,7 +140,7 @@ impl Storage {
             request = request.with_directory(dir);
         }
 
-        self.backend.store(request).await
+        self.store(request).await
     }
 
     /// Upload to a specific directory
@@ -115,7 +154,7 @@ impl Storage {
         self.validate_upload(&content, mime_type)?;
 
         let request = UploadRequest::new(content, filename, mime_type).with_directory(directory);
-        self.backend.store(request).await
+        self.store(request).await
     }
 
     /// Get file contents
@@ -125,7 +164,16 @@ impl Storage {
 
     /// Delete a file
     pub async fn delete(&self, path: &str) -> Result<bool> {
-        self.backend.delete(path).await
+        if self.metered_site().is_none() {
+            return self.backend.delete(path).await;
+        }
+
+        let size = self.backend.size(path).await.ok();
+        let deleted = self.backend.delete(path).await?;
+        if let (true, Some(size)) = (deleted, size) {
+            self.record_files(-(size as i64), -1).await;
+        }
+        Ok(deleted)
     }
 
     /// Check if file exists
@@ -140,7 +188,14 @@ impl Storage {
 
     /// Copy a file
     pub async fn copy(&self, from: &str, to: &str) -> Result<StoredFile> {
-        self.backend.copy(from, to).await
+        if self.metered_site().is_none() {
+            return self.backend.copy(from, to).await;
+        }
+
+        self.check_quota(self.backend.size(from).await?).await?;
+        let file = self.backend.copy(from, to).await?;
+        self.record_files(file.size as i64, 1).await;
+        Ok(file)
     }
 
     /// Move a file
@@ -178,6 +233,271 @@ impl Storage {
         self.backend.name()
     }
 
+    /// Whether clients can upload straight to the backend
+    pub fn supports_direct_upload(&self) -> bool {
+        self.backend.supports_direct_upload()
+    }
+
+    /// Presigned URL for uploading a file in a single PUT
+    ///
+    /// Files larger than 5GB need an upload session instead.
+    pub async fn presigned_upload(
+        &self,
+        filename: &str,
+        mime_type: &str,
+        size: u64,
+        expires_in_secs: u64,
+    ) -> Result<DirectUpload> {
+        if size > MAX_SINGLE_PUT_SIZE {
+            return Err(Error::InvalidInput {
+                field: "file".to_string(),
+                message: format!(
+                    "Files over {} bytes must be uploaded in parts",
+                    MAX_SINGLE_PUT_SIZE
+                ),
+            });
+        }
+        self.validate_direct_upload(size, mime_type)?;
+        self.check_quota(size).await?;
+
+        let path = self.generate_path(filename);
+        let presigned = self
+            .backend
+            .presigned_put_url(&path, expires_in_secs)
+            .await?;
+
+        Ok(DirectUpload { path, presigned })
+    }
+
+    /// Start a resumable multipart upload of `total_size` bytes
+    pub async fn create_upload_session(
+        &self,
+        filename: &str,
+        mime_type: &str,
+        total_size: u64,
+    ) -> Result<UploadSession> {
+        self.validate_direct_upload(total_size, mime_type)?;
+        self.check_quota(total_size).await?;
+
+        let path = self.generate_path(filename);
+        let upload_id = self.backend.create_multipart(&path).await?;
+        let session = UploadSession::new(
+            path,
+            filename,
+            mime_type,
+            total_size,
+            upload_id,
+            chrono::Duration::seconds(self.config.upload_session_ttl_secs as i64),
+        );
+
+        tracing::debug!(
+            session = %session.id,
+            path = %session.path,
+            parts = session.part_count(),
+            "Upload session created"
+        );
+        self.sessions.write().insert(session.id, session.clone());
+        Ok(session)
+    }
+
+    /// Get an upload session
+    pub fn upload_session(&self, id: Uuid) -> Option<UploadSession> {
+        self.sessions.read().get(&id).cloned()
+    }
+
+    /// Presigned URLs for the parts of a session not uploaded yet, so an
+    /// interrupted upload picks up where it stopped
+    pub async fn upload_part_urls(
+        &self,
+        id: Uuid,
+        expires_in_secs: u64,
+    ) -> Result<Vec<PartUploadUrl>> {
+        let session = self.active_session(id)?;
+
+        let mut urls = Vec::new();
+        for part_number in session.missing_parts() {
+            let presigned = self
+                .backend
+                .presigned_part_url(
+                    &session.path,
+                    &session.upload_id,
+                    part_number,
+                    expires_in_secs,
+                )
+                .await?;
+            urls.push(PartUploadUrl {
+                part_number,
+                presigned,
+            });
+        }
+
+        Ok(urls)
+    }
+
+    /// Record a part the client uploaded with a presigned URL
+    pub fn record_uploaded_part(&self, id: Uuid, part: UploadedPart) -> Result<UploadSession> {
+        let mut sessions = self.sessions.write();
+        let session = sessions
+            .get_mut(&id)
+            .filter(|s| !s.is_expired())
+            .ok_or_else(|| session_not_found(id))?;
+
+        if !session.has_part_number(part.part_number) {
+            return Err(Error::InvalidInput {
+                field: "part_number".to_string(),
+                message: format!(
+                    "Part {} is outside 1..={}",
+                    part.part_number,
+                    session.part_count()
+                ),
+            });
+        }
+
+        session.parts.insert(part.part_number, part.etag);
+        Ok(session.clone())
+    }
+
+    /// Upload a part of a session through the server
+    pub async fn upload_session_part(
+        &self,
+        id: Uuid,
+        part_number: u32,
+        content: Bytes,
+    ) -> Result<UploadSession> {
+        let session = self.active_session(id)?;
+        let part = self
+            .backend
+            .upload_part(&session.path, &session.upload_id, part_number, content)
+            .await?;
+        self.record_uploaded_part(id, part)
+    }
+
+    /// Assemble the uploaded parts into the final file
+    pub async fn complete_upload_session(&self, id: Uuid) -> Result<StoredFile> {
+        let session = self.active_session(id)?;
+        let missing = session.missing_parts();
+        if !missing.is_empty() {
+            return Err(Error::InvalidInput {
+                field: "parts".to_string(),
+                message: format!("Parts {:?} have not been uploaded", missing),
+            });
+        }
+
+        self.backend
+            .complete_multipart(&session.path, &session.upload_id, &session.uploaded_parts())
+            .await?;
+        self.sessions.write().remove(&id);
+
+        let mut file = StoredFile::new(
+            &session.path,
+            &session.filename,
+            &session.mime_type,
+            session.total_size,
+        )
+        .with_backend(self.backend.name());
+        if let Some(url) = self.url(&session.path) {
+            file = file.with_url(url);
+        }
+        self.record_files(file.size as i64, 1).await;
+
+        tracing::debug!(session = %id, path = %session.path, "Upload session completed");
+        Ok(file)
+    }
+
+    /// Abort an upload session and drop its parts
+    pub async fn abort_upload_session(&self, id: Uuid) -> Result<()> {
+        let session = self
+            .sessions
+            .write()
+            .remove(&id)
+            .ok_or_else(|| session_not_found(id))?;
+
+        self.backend
+            .abort_multipart(&session.path, &session.upload_id)
+            .await
+    }
+
+    /// Abort sessions past their TTL, returning how many were removed
+    pub async fn purge_expired_upload_sessions(&self) -> usize {
+        let expired: Vec<UploadSession> = {
+            let mut sessions = self.sessions.write();
+            let ids: Vec<Uuid> = sessions
+                .values()
+                .filter(|s| s.is_expired())
+                .map(|s| s.id)
+                .collect();
+            ids.iter().filter_map(|id| sessions.remove(id)).collect()
+        };
+
+        for session in &expired {
+            if let Err(e) = self
+                .backend
+                .abort_multipart(&session.path, &session.upload_id)
+                .await
+            {
+                tracing::warn!(session = %session.id, error = %e, "Failed to abort expired upload");
+            }
+        }
+
+        expired.len()
+    }
+
+    /// Store a file within the site's quotas
+    async fn store(&self, request: UploadRequest) -> Result<StoredFile> {
+        self.check_quota(request.content.len() as u64).await?;
+        let file = self.backend.store(request).await?;
+        self.record_files(file.size as i64, 1).await;
+        Ok(file)
+    }
+
+    /// Site whose usage files count against, if metered
+    fn metered_site(&self) -> Option<(&dyn UsageMeter, Uuid)> {
+        Some((self.meter.as_deref()?, current_site()?))
+    }
+
+    /// Fail when one more file of `size` bytes would take the site over
+    /// its storage or media quota
+    async fn check_quota(&self, size: u64) -> Result<()> {
+        let Some((meter, site)) = self.metered_site() else {
+            return Ok(());
+        };
+        meter.check(site, MeteredResource::Storage, size).await?;
+        meter.check(site, MeteredResource::Media, 1).await
+    }
+
+    /// Count files added (or with negative amounts removed) for the site
+    async fn record_files(&self, bytes: i64, files: i64) {
+        if let Some((meter, site)) = self.metered_site() {
+            meter.record(site, MeteredResource::Storage, bytes).await;
+            meter.record(site, MeteredResource::Media, files).await;
+        }
+    }
+
+    fn active_session(&self, id: Uuid) -> Result<UploadSession> {
+        self.upload_session(id)
+            .filter(|s| !s.is_expired())
+            .ok_or_else(|| session_not_found(id))
+    }
+
+    fn generate_path(&self, filename: &str) -> String {
+        PathGenerator::generate(filename, self.config.default_directory.as_deref())
+    }
+
+    /// Validate an upload going straight to the backend
+    fn validate_direct_upload(&self, size: u64, mime_type: &str) -> Result<()> {
+        if size > self.config.max_direct_upload_size {
+            return Err(Error::InvalidInput {
+                field: "file".to_string(),
+                message: format!(
+                    "File too large. Maximum size is {} bytes",
+                    self.config.max_direct_upload_size
+                ),
+            });
+        }
+
+        self.validate_mime_type(mime_type)
+    }
+
     /// Validate upload
     fn validate_upload(&self, content: &Bytes, mime_type: &str) -> Result<()> {
         // Check size
@@ -191,6 +511,10 @@ impl Storage {
             });
         }
 
+        self.validate_mime_type(mime_type)
+    }
+
+    fn validate_mime_type(&self, mime_type: &str) -> Result<()> {
         // Check denied types
         if self.config.denied_types.iter().any(|t| t == mime_type) {
             return Err(Error::InvalidInput {
@@ -213,6 +537,10 @@ impl Storage {
     }
 }
 
+fn session_not_found(id: Uuid) -> Error {
+    Error::not_found("upload session", id.to_string())
+}
+
 /// MIME type detection utilities
 pub struct MimeDetector;
 
@@ -360,6 +688,212 @@ mod tests {
         assert!(result.is_err());
     }
 
+    /// Local backend that also accepts multipart uploads, assembling the
+    /// parts on completion
+    struct MultipartBackend {
+        inner: LocalBackend,
+        root: std::path::PathBuf,
+        parts: RwLock<HashMap<(String, u32), Bytes>>,
+    }
+
+    #[async_trait::async_trait]
+    impl StorageBackend for MultipartBackend {
+        fn name(&self) -> &str {
+            "multipart"
+        }
+        async fn store(&self, request: UploadRequest) -> Result<StoredFile> {
+            self.inner.store(request).await
+        }
+        async fn get(&self, path: &str) -> Result<Bytes> {
+            self.inner.get(path).await
+        }
+        async fn delete(&self, path: &str) -> Result<bool> {
+            self.inner.delete(path).await
+        }
+        async fn exists(&self, path: &str) -> Result<bool> {
+            self.inner.exists(path).await
+        }
+        async fn size(&self, path: &str) -> Result<u64> {
+            self.inner.size(path).await
+        }
+        async fn copy(&self, from: &str, to: &str) -> Result<StoredFile> {
+            self.inner.copy(from, to).await
+        }
+        async fn move_file(&self, from: &str, to: &str) -> Result<StoredFile> {
+            self.inner.move_file(from, to).await
+        }
+        fn url(&self, path: &str) -> Option<String> {
+            self.inner.url(path)
+        }
+        async fn temporary_url(&self, path: &str, expires_in_secs: u64) -> Result<String> {
+            self.inner.temporary_url(path, expires_in_secs).await
+        }
+        async fn list(&self, prefix: &str) -> Result<Vec<String>> {
+            self.inner.list(prefix).await
+        }
+        async fn health_check(&self) -> Result<()> {
+            self.inner.health_check().await
+        }
+        fn supports_direct_upload(&self) -> bool {
+            true
+        }
+        async fn create_multipart(&self, _path: &str) -> Result<String> {
+            Ok(Uuid::new_v4().to_string())
+        }
+        async fn upload_part(
+            &self,
+            _path: &str,
+            upload_id: &str,
+            part_number: u32,
+            content: Bytes,
+        ) -> Result<UploadedPart> {
+            self.parts
+                .write()
+                .insert((upload_id.to_string(), part_number), content);
+            Ok(UploadedPart {
+                part_number,
+                etag: format!("etag-{}", part_number),
+            })
+        }
+        async fn complete_multipart(
+            &self,
+            path: &str,
+            upload_id: &str,
+            parts: &[UploadedPart],
+        ) -> Result<()> {
+            let mut content = Vec::new();
+            for part in parts {
+                let key = (upload_id.to_string(), part.part_number);
+                content.extend_from_slice(&self.parts.write().remove(&key).unwrap());
+            }
+            let full_path = self.inner_path(path);
+            tokio::fs::create_dir_all(full_path.parent().unwrap())
+                .await
+                .unwrap();
+            tokio::fs::write(full_path, content).await.unwrap();
+            Ok(())
+        }
+        async fn abort_multipart(&self, _path: &str, upload_id: &str) -> Result<()> {
+            self.parts.write().retain(|(id, _), _| id != upload_id);
+            Ok(())
+        }
+    }
+
+    impl MultipartBackend {
+        fn inner_path(&self, path: &str) -> std::path::PathBuf {
+            self.root.join(path)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_upload_session_resume_and_complete() {
+        let temp_dir = TempDir::new().unwrap();
+        let backend = Arc::new(MultipartBackend {
+            inner: LocalBackend::new(temp_dir.path()),
+            root: temp_dir.path().to_path_buf(),
+            parts: RwLock::new(HashMap::new()),
+        });
+        let storage = Storage::new(backend);
+
+        let part_size = crate::upload::DEFAULT_PART_SIZE as usize;
+        let content = Bytes::from(vec![7u8; part_size + 10]);
+        let session = storage
+            .create_upload_session("movie.mp4", "video/mp4", content.len() as u64)
+            .await
+            .unwrap();
+        assert_eq!(session.part_count(), 2);
+
+        storage
+            .upload_session_part(session.id, 2, content.slice(part_size..))
+            .await
+            .unwrap();
+        assert_eq!(
+            storage.upload_session(session.id).unwrap().missing_parts(),
+            vec![1]
+        );
+        assert!(storage.complete_upload_session(session.id).await.is_err());
+
+        storage
+            .upload_session_part(session.id, 1, content.slice(..part_size))
+            .await
+            .unwrap();
+        let file = storage.complete_upload_session(session.id).await.unwrap();
+
+        assert_eq!(file.size, content.len() as u64);
+        assert_eq!(storage.get(&file.path).await.unwrap(), content);
+        assert!(storage.upload_session(session.id).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_local_backend_rejects_direct_upload() {
+        let (storage, _temp) = create_test_storage();
+
+        assert!(!storage.supports_direct_upload());
+        assert!(storage
+            .create_upload_session("movie.mp4", "video/mp4", 1024)
+            .await
+            .is_err());
+        assert!(storage
+            .presigned_upload("movie.mp4", "video/mp4", MAX_SINGLE_PUT_SIZE + 1, 60)
+            .await
+            .is_err());
+    }
+
+    /// Meter allowing one file per site
+    #[derive(Default)]
+    struct OneFileMeter {
+        usage: RwLock<HashMap<(Uuid, MeteredResource), i64>>,
+    }
+
+    #[async_trait::async_trait]
+    impl UsageMeter for OneFileMeter {
+        async fn check(&self, site: Uuid, resource: MeteredResource, amount: u64) -> Result<()> {
+            let used = self
+                .usage
+                .read()
+                .get(&(site, resource))
+                .copied()
+                .unwrap_or(0);
+            if resource == MeteredResource::Media && used + amount as i64 > 1 {
+                return Err(Error::quota_exceeded(
+                    resource.as_str(),
+                    "One file per site",
+                ));
+            }
+            Ok(())
+        }
+
+        async fn record(&self, site: Uuid, resource: MeteredResource, amount: i64) {
+            *self.usage.write().entry((site, resource)).or_default() += amount;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_metered_uploads() {
+        use rustpress_core::tenant::with_site;
+
+        let temp_dir = TempDir::new().unwrap();
+        let meter = Arc::new(OneFileMeter::default());
+        let storage =
+            Storage::new(Arc::new(LocalBackend::new(temp_dir.path()))).with_meter(meter.clone());
+        let site = Uuid::new_v4();
+        let upload = || storage.upload(Bytes::from("Hello"), "hello.txt", "text/plain");
+
+        let file = with_site(Some(site), upload()).await.unwrap();
+        let err = with_site(Some(site), upload()).await.unwrap_err();
+        assert_eq!(err.error_code(), "QUOTA_EXCEEDED");
+        assert_eq!(meter.usage.read()[&(site, MeteredResource::Storage)], 5);
+
+        // The main site is not metered
+        assert!(upload().await.is_ok());
+
+        assert!(with_site(Some(site), storage.delete(&file.path))
+            .await
+            .unwrap());
+        assert_eq!(meter.usage.read()[&(site, MeteredResource::Media)], 0);
+        assert!(with_site(Some(site), upload()).await.is_ok());
+    }
+
     #[test]
     fn test_mime_detector() {
         assert_eq!(MimeDetector::from_extension("jpg"), Some("image/jpeg"));
@@ -0,0 +1,159 @@
+//! Browser-direct and multipart upload sessions.
+
+use crate::presign::PresignedUrl;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+/// Smallest part S3 accepts, except for the last one
+pub const MIN_PART_SIZE: u64 = 5 * 1024 * 1024;
+
+/// Most parts a single multipart upload may have
+pub const MAX_PARTS: u64 = 10_000;
+
+/// Part size used when it keeps the upload within [`MAX_PARTS`]
+pub const DEFAULT_PART_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Part size for an upload of `total_size` bytes
+pub fn part_size_for(total_size: u64) -> u64 {
+    let needed = total_size.div_ceil(MAX_PARTS);
+    needed.max(DEFAULT_PART_SIZE).max(MIN_PART_SIZE)
+}
+
+/// A part the client finished uploading
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UploadedPart {
+    /// 1-based part number
+    pub part_number: u32,
+    /// ETag returned by the object store for the part
+    pub etag: String,
+}
+
+/// Presigned URL for one part of a multipart upload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PartUploadUrl {
+    pub part_number: u32,
+    #[serde(flatten)]
+    pub presigned: PresignedUrl,
+}
+
+/// Multipart upload in progress
+///
+/// Parts are recorded as the client reports them, so an interrupted
+/// upload resumes by asking for the URLs of the missing parts only.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UploadSession {
+    pub id: Uuid,
+    /// Path of the object once the upload completes
+    pub path: String,
+    pub filename: String,
+    pub mime_type: String,
+    pub total_size: u64,
+    pub part_size: u64,
+    /// Upload ID assigned by the object store
+    pub upload_id: String,
+    /// ETags of the uploaded parts by part number
+    pub parts: BTreeMap<u32, String>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl UploadSession {
+    pub fn new(
+        path: impl Into<String>,
+        filename: impl Into<String>,
+        mime_type: impl Into<String>,
+        total_size: u64,
+        upload_id: impl Into<String>,
+        ttl: Duration,
+    ) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4(),
+            path: path.into(),
+            filename: filename.into(),
+            mime_type: mime_type.into(),
+            total_size,
+            part_size: part_size_for(total_size),
+            upload_id: upload_id.into(),
+            parts: BTreeMap::new(),
+            created_at: now,
+            expires_at: now + ttl,
+        }
+    }
+
+    /// Number of parts the upload is split into
+    pub fn part_count(&self) -> u32 {
+        self.total_size.div_ceil(self.part_size).max(1) as u32
+    }
+
+    /// Whether `part_number` belongs to this upload
+    pub fn has_part_number(&self, part_number: u32) -> bool {
+        (1..=self.part_count()).contains(&part_number)
+    }
+
+    /// Part numbers not uploaded yet
+    pub fn missing_parts(&self) -> Vec<u32> {
+        (1..=self.part_count())
+            .filter(|n| !self.parts.contains_key(n))
+            .collect()
+    }
+
+    /// Whether every part was uploaded
+    pub fn is_complete(&self) -> bool {
+        self.missing_parts().is_empty()
+    }
+
+    pub fn is_expired(&self) -> bool {
+        Utc::now() > self.expires_at
+    }
+
+    /// Uploaded parts in part number order
+    pub fn uploaded_parts(&self) -> Vec<UploadedPart> {
+        self.parts
+            .iter()
+            .map(|(part_number, etag)| UploadedPart {
+                part_number: *part_number,
+                etag: etag.clone(),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_part_size_for() {
+        assert_eq!(part_size_for(1024), DEFAULT_PART_SIZE);
+        // 5 TiB needs larger parts to stay within 10,000
+        let total = 5 * 1024 * 1024 * 1024 * 1024;
+        let size = part_size_for(total);
+        assert!(total.div_ceil(size) <= MAX_PARTS);
+    }
+
+    #[test]
+    fn test_session_parts() {
+        let mut session = UploadSession::new(
+            "2024/01/video.mp4",
+            "video.mp4",
+            "video/mp4",
+            DEFAULT_PART_SIZE * 2 + 1,
+            "upload-1",
+            Duration::hours(1),
+        );
+        assert_eq!(session.part_count(), 3);
+        assert!(!session.has_part_number(4));
+
+        session.parts.insert(2, "etag-2".to_string());
+        assert_eq!(session.missing_parts(), vec![1, 3]);
+        assert!(!session.is_complete());
+
+        session.parts.insert(1, "etag-1".to_string());
+        session.parts.insert(3, "etag-3".to_string());
+        assert!(session.is_complete());
+        assert_eq!(session.uploaded_parts()[0].etag, "etag-1");
+    }
+}
@@ -251,32 +251,69 @@ impl ResponsiveImageGenerator {
                 image::imageops::FilterType::Lanczos3,
             ))
         } else {
-            // Calculate dimensions maintaining aspect ratio
-            let (new_width, new_height) = if size.height == 0 {
-                // Only width specified
-                let ratio = size.width as f32 / orig_width as f32;
-                (size.width, (orig_height as f32 * ratio) as u32)
-            } else if size.width == 0 {
-                // Only height specified
-                let ratio = size.height as f32 / orig_height as f32;
-                ((orig_width as f32 * ratio) as u32, size.height)
-            } else {
-                // Both specified, fit within
-                let width_ratio = size.width as f32 / orig_width as f32;
-                let height_ratio = size.height as f32 / orig_height as f32;
-                let ratio = width_ratio.min(height_ratio);
-                (
-                    (orig_width as f32 * ratio) as u32,
-                    (orig_height as f32 * ratio) as u32,
-                )
-            };
+            let (new_width, new_height) = scaled_dimensions(orig_width, orig_height, size)?;
+            Some(img.resize(new_width, new_height, image::imageops::FilterType::Lanczos3))
+        }
+    }
 
-            if new_width >= orig_width && new_height >= orig_height {
-                return None; // Don't upscale
-            }
+    /// Registered image sizes
+    pub fn sizes(&self) -> Vec<ImageSize> {
+        self.sizes.read().clone()
+    }
 
-            Some(img.resize(new_width, new_height, image::imageops::FilterType::Lanczos3))
+    /// A registered image size by name
+    pub fn size(&self, name: &str) -> Option<ImageSize> {
+        self.sizes.read().iter().find(|s| s.name == name).cloned()
+    }
+
+    /// Resize an encoded image to one of the sizes as WebP, `None` when the
+    /// image is too small for it
+    pub fn derive(
+        &self,
+        data: &[u8],
+        size: &ImageSize,
+    ) -> Result<Option<DerivedImage>, ImageError> {
+        let img = image::load_from_memory(data)
+            .map_err(|e| ImageError::ImageProcessing(e.to_string()))?;
+        let Some(resized) = self.resize_image(&img, size) else {
+            return Ok(None);
+        };
+        let (width, height) = resized.dimensions();
+
+        Ok(Some(DerivedImage {
+            data: self.encode_webp(&resized)?,
+            width,
+            height,
+        }))
+    }
+
+    /// Candidates for the `srcset` of an image of the given dimensions, one
+    /// per size that keeps its aspect ratio and is smaller, narrowest first.
+    /// `url_for` names the URL of each; sizes it returns `None` for are left
+    /// out
+    pub fn srcset_candidates(
+        &self,
+        width: u32,
+        height: u32,
+        mut url_for: impl FnMut(&ImageSize) -> Option<String>,
+    ) -> Vec<SrcsetCandidate> {
+        let mut candidates: Vec<SrcsetCandidate> = Vec::new();
+        for size in self.sizes.read().iter().filter(|s| !s.crop) {
+            let Some((scaled_width, _)) = scaled_dimensions(width, height, size) else {
+                continue;
+            };
+            if scaled_width == 0 || candidates.iter().any(|c| c.width == scaled_width) {
+                continue;
+            }
+            if let Some(url) = url_for(size) {
+                candidates.push(SrcsetCandidate {
+                    url,
+                    width: scaled_width,
+                });
+            }
         }
+        candidates.sort_by_key(|c| c.width);
+        candidates
     }
 
     async fn save_optimized(
@@ -399,6 +436,344 @@ impl ResponsiveImageGenerator {
     }
 }
 
+/// Dimensions an image takes at a size that keeps its aspect ratio, `None`
+/// when it would not get smaller
+pub fn scaled_dimensions(width: u32, height: u32, size: &ImageSize) -> Option<(u32, u32)> {
+    if width == 0 || height == 0 || (size.width == 0 && size.height == 0) {
+        return None;
+    }
+
+    let (new_width, new_height) = if size.height == 0 {
+        // Only width specified
+        let ratio = size.width as f32 / width as f32;
+        (size.width, (height as f32 * ratio) as u32)
+    } else if size.width == 0 {
+        // Only height specified
+        let ratio = size.height as f32 / height as f32;
+        ((width as f32 * ratio) as u32, size.height)
+    } else {
+        // Both specified, fit within
+        let width_ratio = size.width as f32 / width as f32;
+        let height_ratio = size.height as f32 / height as f32;
+        let ratio = width_ratio.min(height_ratio);
+        (
+            (width as f32 * ratio) as u32,
+            (height as f32 * ratio) as u32,
+        )
+    };
+
+    if new_width >= width && new_height >= height {
+        return None; // Don't upscale
+    }
+
+    Some((new_width.max(1), new_height.max(1)))
+}
+
+/// Width and height of an encoded image, read from its header
+pub fn probe_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    image::ImageReader::new(std::io::Cursor::new(data))
+        .with_guessed_format()
+        .ok()?
+        .into_dimensions()
+        .ok()
+}
+
+/// An image resized to one of the registered sizes
+#[derive(Debug, Clone)]
+pub struct DerivedImage {
+    /// WebP encoded image
+    pub data: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// One image URL of a `srcset`, with its width
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct SrcsetCandidate {
+    pub url: String,
+    pub width: u32,
+}
+
+/// What is known about an image referenced by rendered HTML
+#[derive(Debug, Clone, Default)]
+pub struct KnownImage {
+    /// Intrinsic width of the original
+    pub width: u32,
+    /// Intrinsic height of the original
+    pub height: u32,
+    /// Smaller versions of the image, see
+    /// [`ResponsiveImageGenerator::srcset_candidates`]
+    pub srcset: Vec<SrcsetCandidate>,
+}
+
+/// How images and iframes in rendered HTML are loaded
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct LoadingPolicy {
+    /// Add `loading="lazy"` to images
+    pub lazy_images: bool,
+    /// Add `loading="lazy"` to iframes
+    pub lazy_iframes: bool,
+    /// Images at the top of the page left to load eagerly, as one of them
+    /// is likely the largest contentful paint
+    pub eager_images: usize,
+    /// Add `decoding="async"` to images
+    pub decoding_async: bool,
+    /// Add `srcset` and `sizes` to images with smaller versions
+    pub srcset: bool,
+}
+
+impl Default for LoadingPolicy {
+    fn default() -> Self {
+        Self {
+            lazy_images: true,
+            lazy_iframes: true,
+            eager_images: 1,
+            decoding_async: true,
+            srcset: true,
+        }
+    }
+}
+
+/// Class or attribute that keeps an image or iframe from being lazy-loaded
+const SKIP_LAZY: &str = "skip-lazy";
+
+/// Elements whose content is not markup
+const RAW_TEXT_ELEMENTS: &[&str] = &["script", "style", "noscript", "template", "textarea"];
+
+fn media_tag_regex() -> &'static regex::Regex {
+    static RE: std::sync::OnceLock<regex::Regex> = std::sync::OnceLock::new();
+    RE.get_or_init(|| {
+        regex::Regex::new(r"(?i)<(img|iframe|script|style|noscript|template|textarea)\b[^>]*>")
+            .unwrap()
+    })
+}
+
+fn attribute_regex() -> &'static regex::Regex {
+    static RE: std::sync::OnceLock<regex::Regex> = std::sync::OnceLock::new();
+    RE.get_or_init(|| {
+        regex::Regex::new(r#"([^\s"'<>/=]+)(?:\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'=<>`]+)))?"#)
+            .unwrap()
+    })
+}
+
+/// `<img>` and `<iframe>` tags of a document, with their byte ranges;
+/// anything inside scripts, styles, `<noscript>` and the like is skipped
+fn media_tags(html: &str) -> Vec<(std::ops::Range<usize>, bool)> {
+    let re = media_tag_regex();
+    let lower = html.to_ascii_lowercase();
+    let mut tags = Vec::new();
+    let mut at = 0;
+
+    while let Some(found) = re.captures_at(html, at) {
+        let whole = found.get(0).unwrap();
+        let name = found[1].to_ascii_lowercase();
+        at = whole.end();
+
+        if RAW_TEXT_ELEMENTS.contains(&name.as_str()) {
+            let close = format!("</{}", name);
+            match lower[at..].find(&close) {
+                Some(offset) => at += offset + close.len(),
+                None => break,
+            }
+            continue;
+        }
+        tags.push((whole.range(), name == "img"));
+    }
+
+    tags
+}
+
+/// Attributes of a tag, names lowercased; attributes without a value
+/// map to an empty string
+fn tag_attributes(tag: &str) -> HashMap<String, String> {
+    let body = tag
+        .trim_start_matches('<')
+        .trim_end_matches('>')
+        .trim_end_matches('/');
+    let body = body
+        .find(|c: char| c.is_ascii_whitespace())
+        .map_or("", |i| &body[i..]);
+
+    attribute_regex()
+        .captures_iter(body)
+        .map(|c| {
+            let value = c
+                .get(2)
+                .or_else(|| c.get(3))
+                .or_else(|| c.get(4))
+                .map_or("", |m| m.as_str());
+            (c[1].to_ascii_lowercase(), value.to_string())
+        })
+        .collect()
+}
+
+/// Sources of the images in rendered HTML, in document order; inline
+/// `data:` images are left out
+pub fn image_sources(html: &str) -> Vec<String> {
+    let mut sources: Vec<String> = Vec::new();
+    for (range, is_image) in media_tags(html) {
+        if !is_image {
+            continue;
+        }
+        if let Some(src) = tag_attributes(&html[range]).remove("src") {
+            if !src.is_empty() && !src.starts_with("data:") && !sources.contains(&src) {
+                sources.push(src);
+            }
+        }
+    }
+    sources
+}
+
+/// Post-process rendered HTML for loading performance. Images get
+///
+/// - `width` and `height` when `lookup` knows the image, so the browser
+///   reserves their space and the layout does not shift
+/// - `loading="lazy"` after the first [`LoadingPolicy::eager_images`], and
+///   `decoding="async"`
+/// - `srcset` and `sizes` from the known smaller versions
+///
+/// and iframes `loading="lazy"`. Attributes already present are kept, and
+/// elements with the `skip-lazy` class or a `data-skip-lazy` attribute are
+/// never lazy-loaded
+pub fn optimize_images<'a>(
+    html: &str,
+    policy: &LoadingPolicy,
+    lookup: impl Fn(&str) -> Option<&'a KnownImage>,
+) -> String {
+    let mut out = String::with_capacity(html.len() + html.len() / 8);
+    let mut last = 0;
+    let mut images = 0;
+
+    for (range, is_image) in media_tags(html) {
+        let tag = &html[range.clone()];
+        let attributes = tag_attributes(tag);
+        let additions = if is_image {
+            images += 1;
+            image_additions(&attributes, policy, images > policy.eager_images, &lookup)
+        } else {
+            iframe_additions(&attributes, policy)
+        };
+        if additions.is_empty() {
+            continue;
+        }
+
+        out.push_str(&html[last..range.start]);
+        let (body, close) = match tag.strip_suffix("/>") {
+            Some(body) => (body.trim_end(), " />"),
+            None => (tag[..tag.len() - 1].trim_end(), ">"),
+        };
+        out.push_str(body);
+        for (name, value) in additions {
+            // Values are attribute text already, only quotes need escaping
+            out.push_str(&format!(" {}=\"{}\"", name, value.replace('"', "&quot;")));
+        }
+        out.push_str(close);
+        last = range.end;
+    }
+
+    out.push_str(&html[last..]);
+    out
+}
+
+fn skips_lazy(attributes: &HashMap<String, String>) -> bool {
+    attributes.contains_key("data-skip-lazy")
+        || attributes
+            .get("class")
+            .is_some_and(|class| class.split_ascii_whitespace().any(|c| c == SKIP_LAZY))
+}
+
+fn image_additions<'a>(
+    attributes: &HashMap<String, String>,
+    policy: &LoadingPolicy,
+    below_fold: bool,
+    lookup: &impl Fn(&str) -> Option<&'a KnownImage>,
+) -> Vec<(&'static str, String)> {
+    let mut additions = Vec::new();
+    let Some(src) = attributes
+        .get("src")
+        .filter(|src| !src.starts_with("data:"))
+    else {
+        return additions;
+    };
+    let known = lookup(src).filter(|k| k.width > 0 && k.height > 0);
+    let dimension = |name: &str| {
+        attributes
+            .get(name)
+            .and_then(|v| v.trim().trim_end_matches("px").parse::<u32>().ok())
+    };
+    let (width, height) = (dimension("width"), dimension("height"));
+
+    if let Some(known) = known {
+        let ratio = known.height as f64 / known.width as f64;
+        match (
+            attributes.contains_key("width"),
+            attributes.contains_key("height"),
+        ) {
+            (false, false) => {
+                additions.push(("width", known.width.to_string()));
+                additions.push(("height", known.height.to_string()));
+            }
+            (true, false) => {
+                if let Some(width) = width {
+                    additions.push((
+                        "height",
+                        ((width as f64 * ratio).round() as u32).to_string(),
+                    ));
+                }
+            }
+            (false, true) => {
+                if let Some(height) = height {
+                    additions.push((
+                        "width",
+                        ((height as f64 / ratio).round() as u32).to_string(),
+                    ));
+                }
+            }
+            (true, true) => {}
+        }
+
+        if policy.srcset && !known.srcset.is_empty() && !attributes.contains_key("srcset") {
+            let mut candidates: Vec<String> = known
+                .srcset
+                .iter()
+                .filter(|c| c.width < known.width)
+                .map(|c| format!("{} {}w", c.url, c.width))
+                .collect();
+            candidates.push(format!("{} {}w", src, known.width));
+            additions.push(("srcset", candidates.join(", ")));
+            if !attributes.contains_key("sizes") {
+                let shown = width.unwrap_or(known.width);
+                additions.push(("sizes", format!("(max-width: {0}px) 100vw, {0}px", shown)));
+            }
+        }
+    }
+
+    if policy.lazy_images
+        && below_fold
+        && !attributes.contains_key("loading")
+        && !skips_lazy(attributes)
+    {
+        additions.push(("loading", "lazy".to_string()));
+    }
+    if policy.decoding_async && !attributes.contains_key("decoding") {
+        additions.push(("decoding", "async".to_string()));
+    }
+
+    additions
+}
+
+fn iframe_additions(
+    attributes: &HashMap<String, String>,
+    policy: &LoadingPolicy,
+) -> Vec<(&'static str, String)> {
+    if policy.lazy_iframes && !attributes.contains_key("loading") && !skips_lazy(attributes) {
+        vec![("loading", "lazy".to_string())]
+    } else {
+        Vec::new()
+    }
+}
+
 /// HTML escape helper
 fn html_escape(s: &str) -> String {
     s.replace('&', "&amp;")
@@ -654,6 +1029,92 @@ mod tests {
         assert_eq!(html_escape(r#"He said "hi""#), "He said &quot;hi&quot;");
     }
 
+    #[test]
+    fn test_scaled_dimensions() {
+        let medium = default_image_sizes().remove(1);
+        assert_eq!(scaled_dimensions(1200, 800, &medium), Some((300, 200)));
+        assert_eq!(scaled_dimensions(200, 100, &medium), None);
+    }
+
+    #[test]
+    fn test_srcset_candidates() {
+        let generator = ResponsiveImageGenerator::new(PathBuf::from("sizes"));
+        let candidates = generator.srcset_candidates(1200, 800, |size| {
+            (size.name != "large").then(|| format!("/{}.webp", size.name))
+        });
+        // Thumbnails are cropped and larger sizes would upscale
+        assert_eq!(
+            candidates,
+            vec![
+                SrcsetCandidate {
+                    url: "/medium.webp".to_string(),
+                    width: 300
+                },
+                SrcsetCandidate {
+                    url: "/medium_large.webp".to_string(),
+                    width: 768
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_derive_and_probe() {
+        let img = DynamicImage::new_rgb8(800, 400);
+        let mut png = std::io::Cursor::new(Vec::new());
+        img.write_to(&mut png, ImageFormat::Png).unwrap();
+        let png = png.into_inner();
+        assert_eq!(probe_dimensions(&png), Some((800, 400)));
+
+        let generator = ResponsiveImageGenerator::new(PathBuf::from("sizes"));
+        let medium = generator.size("medium").unwrap();
+        let derived = generator.derive(&png, &medium).unwrap().unwrap();
+        assert_eq!((derived.width, derived.height), (300, 150));
+        assert_eq!(probe_dimensions(&derived.data), Some((300, 150)));
+        let large = generator.size("large").unwrap();
+        assert!(generator.derive(&png, &large).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_optimize_images() {
+        let known = KnownImage {
+            width: 1200,
+            height: 800,
+            srcset: vec![SrcsetCandidate {
+                url: "/media/transform/1/medium".to_string(),
+                width: 300,
+            }],
+        };
+        let html = concat!(
+            r#"<img src="/uploads/hero.jpg" alt="Hero">"#,
+            r#"<p><img src='/uploads/hero.jpg' width="600" class="photo"/></p>"#,
+            r#"<img src="/other.png" loading="eager" class="a skip-lazy">"#,
+            r#"<script>var s = '<img src="x">';</script>"#,
+            r#"<iframe src="https://video.example/embed"></iframe>"#,
+        );
+        let out = optimize_images(html, &LoadingPolicy::default(), |src| {
+            (src == "/uploads/hero.jpg").then_some(&known)
+        });
+
+        assert!(out.starts_with(concat!(
+            r#"<img src="/uploads/hero.jpg" alt="Hero" width="1200" height="800" "#,
+            r#"srcset="/media/transform/1/medium 300w, /uploads/hero.jpg 1200w" "#,
+            r#"sizes="(max-width: 1200px) 100vw, 1200px" decoding="async">"#
+        )));
+        assert!(out.contains(concat!(
+            r#"width="600" class="photo" height="400" "#,
+            r#"srcset="/media/transform/1/medium 300w, /uploads/hero.jpg 1200w" "#,
+            r#"sizes="(max-width: 600px) 100vw, 600px" loading="lazy" decoding="async" />"#
+        )));
+        assert!(out.contains(r#"class="a skip-lazy" decoding="async">"#));
+        assert!(out.contains(r#"<script>var s = '<img src="x">';</script>"#));
+        assert!(out.contains(r#"<iframe src="https://video.example/embed" loading="lazy">"#));
+        assert_eq!(
+            image_sources(html),
+            vec!["/uploads/hero.jpg".to_string(), "/other.png".to_string()]
+        );
+    }
+
     #[test]
     fn test_base64_encode() {
         let encoded = base64_encode(b"Hello");
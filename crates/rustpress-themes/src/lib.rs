@@ -11,6 +11,7 @@
 //! - Full-site editing support
 //! - Theme variations and dark mode
 //! - Accessibility and performance tools
+//! - Structured data (JSON-LD/microdata) validation
 
 pub mod assets;
 pub mod child_theme;
@@ -28,6 +29,7 @@ pub mod patterns;
 pub mod quality;
 pub mod settings;
 pub mod starter_content;
+pub mod structured_data;
 pub mod templates;
 pub mod theme_json;
 pub mod variations;
@@ -41,7 +43,10 @@ pub use design_tokens::{ColorPalette, DesignTokens, LayoutSettings, TypographySe
 pub use docs::{DocGenerator, ScreenshotGenerator};
 pub use export::{ExportOptions, ThemeExporter, ThemeImporter};
 pub use fse::{FseManager, FseTemplate, TemplatePart};
-pub use images::{ImageSize, ResponsiveImageGenerator};
+pub use images::{
+    image_sources, optimize_images, ImageSize, KnownImage, LoadingPolicy, ResponsiveImageGenerator,
+    SrcsetCandidate,
+};
 pub use manager::{RegisteredTheme, ThemeManager, ThemePreview};
 pub use manifest::ThemeManifest;
 pub use marketplace::{MarketplaceClient, MarketplaceConfig, ThemeListing};
@@ -49,6 +54,7 @@ pub use patterns::{BlockPattern, PatternRegistry};
 pub use quality::{AccessibilityChecker, AmpCompatibility, PerformanceScorer};
 pub use settings::{GlobalSettingsRegistry, ThemeSettings};
 pub use starter_content::StarterContent;
+pub use structured_data::{StructuredDataAudit, StructuredDataReport, StructuredDataValidator};
 pub use templates::{TemplateEngine, TemplateHierarchy, TemplatePartManager};
 pub use theme_json::ThemeJson;
 pub use variations::{DarkModeConfig, StyleVariation, VariationManager};
@@ -7,7 +7,9 @@ use crate::manifest::ThemeManifest;
 use crate::settings::ThemeSettings;
 use crate::templates::{TemplateEngine, TemplateError};
 use parking_lot::RwLock;
-use std::collections::HashMap;
+use rustpress_core::compat::{self, CompatProfile, CompatReport, Platform};
+use rustpress_core::tenant::ExtensionKind;
+use std::collections::{BTreeSet, HashMap};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use thiserror::Error;
@@ -40,6 +42,12 @@ pub enum ThemeManagerError {
 
     #[error("Theme deactivation failed: {0}")]
     DeactivationFailed(String),
+
+    #[error("Theme not allowed on this network: {0}")]
+    NotAllowed(String),
+
+    #[error("Theme {} is incompatible", .0.id)]
+    Incompatible(CompatReport),
 }
 
 /// Theme status
@@ -86,6 +94,10 @@ pub struct ThemeManager {
     engines: Arc<RwLock<HashMap<String, Arc<TemplateEngine>>>>,
     /// Event broadcaster
     event_tx: broadcast::Sender<ThemeEvent>,
+    /// Theme IDs permitted on this network (`None` allows any)
+    allowlist: Arc<RwLock<Option<BTreeSet<String>>>>,
+    /// What this installation provides, checked on activation
+    platform: Arc<RwLock<Platform>>,
 }
 
 /// Theme events
@@ -111,9 +123,72 @@ impl ThemeManager {
             settings: Arc::new(RwLock::new(HashMap::new())),
             engines: Arc::new(RwLock::new(HashMap::new())),
             event_tx,
+            allowlist: Arc::new(RwLock::new(None)),
+            platform: Arc::new(RwLock::new(Platform::current())),
         }
     }
 
+    /// Restrict which themes may be installed or activated
+    pub fn set_allowlist(&self, allowlist: Option<BTreeSet<String>>) {
+        *self.allowlist.write() = allowlist;
+    }
+
+    /// Get the current theme allowlist
+    pub fn allowlist(&self) -> Option<BTreeSet<String>> {
+        self.allowlist.read().clone()
+    }
+
+    /// Check whether a theme is permitted by the allowlist
+    pub fn is_allowed(&self, theme_id: &str) -> bool {
+        self.allowlist
+            .read()
+            .as_ref()
+            .is_none_or(|ids| ids.contains(theme_id))
+    }
+
+    pub fn set_platform(&self, platform: Platform) {
+        *self.platform.write() = platform;
+    }
+
+    pub fn platform(&self) -> Platform {
+        self.platform.read().clone()
+    }
+
+    /// Compatibility profile of a registered theme
+    pub fn compat_profile(&self, theme_id: &str) -> Option<CompatProfile> {
+        let themes = self.themes.read();
+        let meta = &themes.get(theme_id)?.manifest.theme;
+
+        let mut profile = CompatProfile::new(ExtensionKind::Theme, theme_id, &meta.version);
+        profile.min_rustpress_version = meta.requires_rustpress.as_deref().and_then(parse_version);
+        profile.required_features = meta.requires_features.clone();
+        Some(profile)
+    }
+
+    /// Check a theme against the platform and the given active extensions
+    pub fn check_compatibility(
+        &self,
+        theme_id: &str,
+        active: &[CompatProfile],
+    ) -> Result<CompatReport, ThemeManagerError> {
+        let profile = self
+            .compat_profile(theme_id)
+            .ok_or_else(|| ThemeManagerError::NotFound(theme_id.to_string()))?;
+        Ok(compat::check(&self.platform.read(), &profile, active))
+    }
+
+    /// Check every registered theme against the platform and the given
+    /// active extensions
+    pub fn audit_compatibility(&self, active: &[CompatProfile]) -> Vec<CompatReport> {
+        let mut ids: Vec<String> = self.themes.read().keys().cloned().collect();
+        ids.sort();
+        let profiles: Vec<CompatProfile> = ids
+            .iter()
+            .filter_map(|id| self.compat_profile(id))
+            .collect();
+        compat::audit(&self.platform.read(), &profiles, active)
+    }
+
     /// Scan and register all themes in the themes directory
     pub async fn scan_themes(&self) -> Result<Vec<String>, ThemeManagerError> {
         let mut registered = Vec::new();
@@ -265,6 +340,10 @@ impl ThemeManager {
 
     /// Activate a theme
     pub async fn activate(&self, theme_id: &str) -> Result<(), ThemeManagerError> {
+        if !self.is_allowed(theme_id) {
+            return Err(ThemeManagerError::NotAllowed(theme_id.to_string()));
+        }
+
         // Get theme
         let theme = self
             .themes
@@ -273,6 +352,11 @@ impl ThemeManager {
             .cloned()
             .ok_or_else(|| ThemeManagerError::NotFound(theme_id.to_string()))?;
 
+        let report = self.check_compatibility(theme_id, &[])?;
+        if !report.is_compatible() {
+            return Err(ThemeManagerError::Incompatible(report));
+        }
+
         // Deactivate current theme if any
         let old_theme = self.active_theme.read().clone();
         if let Some(ref old_id) = old_theme {
@@ -466,6 +550,10 @@ impl ThemeManager {
 
         let theme_id = manifest.theme.id.clone();
 
+        if !self.is_allowed(&theme_id) {
+            return Err(ThemeManagerError::NotAllowed(theme_id));
+        }
+
         // Check if already exists
         let dest = self.themes_dir.join(&theme_id);
         if dest.exists() {
@@ -501,6 +589,17 @@ async fn copy_dir_recursive(src: &Path, dst: &Path) -> Result<(), std::io::Error
     Ok(())
 }
 
+/// Parse a version from a manifest, accepting short forms like `1.2`
+fn parse_version(version: &str) -> Option<semver::Version> {
+    let version = version.trim().trim_start_matches('v');
+    let padded = match version.matches('.').count() {
+        0 => format!("{}.0.0", version),
+        1 => format!("{}.0", version),
+        _ => version.to_string(),
+    };
+    semver::Version::parse(&padded).ok()
+}
+
 /// Theme preview manager for live previewing themes
 pub struct ThemePreview {
     manager: Arc<ThemeManager>,
@@ -614,4 +713,46 @@ mod tests {
         let themes = manager.scan_themes().await.unwrap();
         assert!(themes.is_empty());
     }
+
+    #[tokio::test]
+    async fn test_activate_respects_allowlist() {
+        let dir = tempdir().unwrap();
+        let manager = ThemeManager::new(dir.path().to_path_buf());
+        manager.set_allowlist(Some(BTreeSet::from(["starter".to_string()])));
+
+        assert!(manager.is_allowed("starter"));
+        assert!(!manager.is_allowed("premium"));
+        let err = manager.activate("premium").await.unwrap_err();
+        assert!(matches!(err, ThemeManagerError::NotAllowed(id) if id == "premium"));
+    }
+
+    #[tokio::test]
+    async fn test_activate_checks_compatibility() {
+        let dir = tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("semantic"))
+            .await
+            .unwrap();
+        fs::write(
+            dir.path().join("semantic/theme.json"),
+            r#"{"name": "Semantic", "requires_rustpress": "99.0", "requires_features": ["pgvector"]}"#,
+        )
+        .await
+        .unwrap();
+
+        let manager = ThemeManager::new(dir.path().to_path_buf());
+        manager.register_theme("semantic").await.unwrap();
+
+        let profile = manager.compat_profile("semantic").unwrap();
+        assert_eq!(
+            profile.min_rustpress_version,
+            Some(semver::Version::new(99, 0, 0))
+        );
+
+        let err = manager.activate("semantic").await.unwrap_err();
+        let ThemeManagerError::Incompatible(report) = err else {
+            panic!("expected an incompatibility report");
+        };
+        assert_eq!(report.errors().count(), 2);
+        assert!(manager.get_active_id().is_none());
+    }
 }
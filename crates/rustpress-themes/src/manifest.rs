@@ -114,6 +114,10 @@ pub struct ThemeMeta {
     #[serde(default)]
     pub tested_up_to: Option<String>,
 
+    /// Platform features the theme needs, such as `pgvector` or `redis`
+    #[serde(default)]
+    pub requires_features: Vec<String>,
+
     /// Text domain for translations
     #[serde(default)]
     pub text_domain: Option<String>,
@@ -1152,6 +1156,15 @@ impl ThemeManifest {
                 .get("tested_up_to")
                 .and_then(|v| v.as_str())
                 .map(|s| s.to_string()),
+            requires_features: json
+                .get("requires_features")
+                .and_then(|v| v.as_array())
+                .map(|arr| {
+                    arr.iter()
+                        .filter_map(|v| v.as_str().map(|s| s.to_string()))
+                        .collect()
+                })
+                .unwrap_or_default(),
             text_domain: json
                 .get("text_domain")
                 .and_then(|v| v.as_str())
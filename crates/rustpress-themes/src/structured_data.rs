@@ -0,0 +1,636 @@
+//! Structured Data Validation
+//!
+//! Extracts JSON-LD and microdata from rendered pages and checks the
+//! properties search engines require for common schema.org types (Article,
+//! Product, Event, BreadcrumbList). Page reports can be rolled up per template so
+//! schema regressions introduced by a theme change show up before deploy.
+
+use std::collections::BTreeMap;
+
+use scraper::{ElementRef, Html, Selector};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::quality::Severity;
+
+/// Recommended maximum headline length for rich results
+const MAX_HEADLINE_LENGTH: usize = 110;
+
+/// Where a structured data item was found
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StructuredDataSource {
+    JsonLd,
+    Microdata,
+}
+
+/// A structured data item extracted from a page
+#[derive(Debug, Clone, Serialize)]
+pub struct StructuredItem {
+    pub source: StructuredDataSource,
+    /// schema.org type without the vocabulary prefix, e.g. `Article`
+    pub item_type: String,
+    /// Properties as a JSON object; microdata is converted to the same shape
+    pub properties: Map<String, Value>,
+}
+
+/// A structured data problem
+#[derive(Debug, Clone, Serialize)]
+pub struct StructuredDataIssue {
+    pub severity: Severity,
+    pub item_type: Option<String>,
+    pub property: Option<String>,
+    pub message: String,
+}
+
+/// Validation result for one page
+#[derive(Debug, Clone, Serialize)]
+pub struct StructuredDataReport {
+    /// Types of all items found, in document order
+    pub item_types: Vec<String>,
+    pub issues: Vec<StructuredDataIssue>,
+    pub errors: usize,
+    pub warnings: usize,
+    pub passed: bool,
+}
+
+/// Required and recommended properties for a family of types
+struct TypeRule {
+    types: &'static [&'static str],
+    required: &'static [&'static str],
+    recommended: &'static [&'static str],
+    check: fn(&StructuredItem, &mut Vec<StructuredDataIssue>),
+}
+
+/// Structured data validator
+pub struct StructuredDataValidator {
+    rules: Vec<TypeRule>,
+}
+
+impl StructuredDataValidator {
+    pub fn new() -> Self {
+        Self {
+            rules: vec![
+                TypeRule {
+                    types: &["Article", "BlogPosting", "NewsArticle", "TechArticle"],
+                    required: &["headline", "author", "datePublished"],
+                    recommended: &["image", "dateModified", "publisher"],
+                    check: check_article,
+                },
+                TypeRule {
+                    types: &["Product"],
+                    required: &["name"],
+                    recommended: &["image", "description", "sku", "brand"],
+                    check: check_product,
+                },
+                TypeRule {
+                    types: &["Event", "MusicEvent", "BusinessEvent", "EducationEvent"],
+                    required: &["name", "startDate", "location"],
+                    recommended: &["endDate", "eventStatus", "image", "description", "offers"],
+                    check: check_event,
+                },
+                TypeRule {
+                    types: &["BreadcrumbList"],
+                    required: &["itemListElement"],
+                    recommended: &[],
+                    check: check_breadcrumbs,
+                },
+            ],
+        }
+    }
+
+    /// Extract all JSON-LD and microdata items from a page
+    pub fn extract(&self, html: &str) -> (Vec<StructuredItem>, Vec<StructuredDataIssue>) {
+        let document = Html::parse_document(html);
+        let mut items = Vec::new();
+        let mut issues = Vec::new();
+
+        let scripts = Selector::parse(r#"script[type="application/ld+json"]"#).unwrap();
+        for (index, script) in document.select(&scripts).enumerate() {
+            let text: String = script.text().collect();
+            match serde_json::from_str::<Value>(&text) {
+                Ok(value) => collect_json_ld(&value, &mut items),
+                Err(e) => issues.push(StructuredDataIssue {
+                    severity: Severity::Error,
+                    item_type: None,
+                    property: None,
+                    message: format!("JSON-LD block {} is not valid JSON: {}", index + 1, e),
+                }),
+            }
+        }
+
+        // Top-level microdata items; nested items are collected as properties
+        let scopes = Selector::parse("[itemscope]:not([itemprop])").unwrap();
+        for scope in document.select(&scopes) {
+            if let Some(item) = microdata_item(scope) {
+                items.push(item);
+            }
+        }
+
+        (items, issues)
+    }
+
+    /// Validate all structured data on a page
+    pub fn validate(&self, html: &str) -> StructuredDataReport {
+        let (items, mut issues) = self.extract(html);
+
+        for item in &items {
+            let Some(rule) = self
+                .rules
+                .iter()
+                .find(|rule| rule.types.contains(&item.item_type.as_str()))
+            else {
+                continue;
+            };
+
+            for property in rule.required {
+                if !has_property(&item.properties, property) {
+                    issues.push(item_issue(
+                        Severity::Error,
+                        item,
+                        property,
+                        format!("Missing required property '{}'", property),
+                    ));
+                }
+            }
+            for property in rule.recommended {
+                if !has_property(&item.properties, property) {
+                    issues.push(item_issue(
+                        Severity::Warning,
+                        item,
+                        property,
+                        format!("Missing recommended property '{}'", property),
+                    ));
+                }
+            }
+            (rule.check)(item, &mut issues);
+        }
+
+        let errors = issues
+            .iter()
+            .filter(|i| i.severity == Severity::Error)
+            .count();
+        let warnings = issues
+            .iter()
+            .filter(|i| i.severity == Severity::Warning)
+            .count();
+
+        StructuredDataReport {
+            item_types: items.iter().map(|i| i.item_type.clone()).collect(),
+            issues,
+            errors,
+            warnings,
+            passed: errors == 0,
+        }
+    }
+}
+
+impl Default for StructuredDataValidator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn item_issue(
+    severity: Severity,
+    item: &StructuredItem,
+    property: &str,
+    message: String,
+) -> StructuredDataIssue {
+    StructuredDataIssue {
+        severity,
+        item_type: Some(item.item_type.clone()),
+        property: Some(property.to_string()),
+        message,
+    }
+}
+
+fn check_article(item: &StructuredItem, issues: &mut Vec<StructuredDataIssue>) {
+    if let Some(headline) = item.properties.get("headline").and_then(Value::as_str) {
+        if headline.chars().count() > MAX_HEADLINE_LENGTH {
+            issues.push(item_issue(
+                Severity::Warning,
+                item,
+                "headline",
+                format!("Headline is longer than {} characters", MAX_HEADLINE_LENGTH),
+            ));
+        }
+    }
+
+    for property in ["datePublished", "dateModified"] {
+        if let Some(date) = item.properties.get(property).and_then(Value::as_str) {
+            if !is_iso8601(date) {
+                issues.push(item_issue(
+                    Severity::Error,
+                    item,
+                    property,
+                    format!("'{}' is not an ISO 8601 date", date),
+                ));
+            }
+        }
+    }
+}
+
+fn check_product(item: &StructuredItem, issues: &mut Vec<StructuredDataIssue>) {
+    if !["offers", "review", "aggregateRating"]
+        .iter()
+        .any(|property| has_property(&item.properties, property))
+    {
+        issues.push(StructuredDataIssue {
+            severity: Severity::Error,
+            item_type: Some(item.item_type.clone()),
+            property: None,
+            message: "Product needs one of 'offers', 'review' or 'aggregateRating'".to_string(),
+        });
+    }
+
+    for offer in values(item.properties.get("offers")) {
+        let Some(offer) = offer.as_object() else {
+            continue;
+        };
+        // AggregateOffer uses lowPrice instead of price
+        let priced = has_property(offer, "price") || has_property(offer, "lowPrice");
+        if !priced || !has_property(offer, "priceCurrency") {
+            issues.push(item_issue(
+                Severity::Error,
+                item,
+                "offers",
+                "Offer needs 'price' and 'priceCurrency'".to_string(),
+            ));
+        }
+    }
+}
+
+fn check_event(item: &StructuredItem, issues: &mut Vec<StructuredDataIssue>) {
+    for property in ["startDate", "endDate"] {
+        if let Some(date) = item.properties.get(property).and_then(Value::as_str) {
+            if !is_iso8601(date) {
+                issues.push(item_issue(
+                    Severity::Error,
+                    item,
+                    property,
+                    format!("'{}' is not an ISO 8601 date", date),
+                ));
+            }
+        }
+    }
+
+    // A Place needs an address, a VirtualLocation a URL
+    for location in values(item.properties.get("location")) {
+        let Some(location) = location.as_object() else {
+            continue;
+        };
+        let kind = location
+            .get("@type")
+            .and_then(Value::as_str)
+            .map(short_type)
+            .unwrap_or_default();
+        let (property, message) = if kind == "VirtualLocation" {
+            ("url", "Virtual location needs a 'url'")
+        } else {
+            ("address", "Event location needs an 'address'")
+        };
+        if !has_property(location, property) {
+            issues.push(item_issue(
+                Severity::Error,
+                item,
+                "location",
+                message.to_string(),
+            ));
+        }
+    }
+}
+
+fn check_breadcrumbs(item: &StructuredItem, issues: &mut Vec<StructuredDataIssue>) {
+    let elements = values(item.properties.get("itemListElement"));
+    let last = elements.len().saturating_sub(1);
+
+    for (index, element) in elements.iter().enumerate() {
+        let Some(element) = element.as_object() else {
+            continue;
+        };
+        let name = has_property(element, "name")
+            || element
+                .get("item")
+                .and_then(Value::as_object)
+                .is_some_and(|item| has_property(item, "name"));
+        let position = element.get("position").is_some_and(|position| {
+            position.as_u64().is_some()
+                || position.as_str().is_some_and(|p| p.parse::<u64>().is_ok())
+        });
+
+        if !position {
+            issues.push(item_issue(
+                Severity::Error,
+                item,
+                "itemListElement",
+                format!("Breadcrumb {} needs an integer 'position'", index + 1),
+            ));
+        }
+        if !name {
+            issues.push(item_issue(
+                Severity::Error,
+                item,
+                "itemListElement",
+                format!("Breadcrumb {} needs a 'name'", index + 1),
+            ));
+        }
+        // Only the current page may omit its URL
+        if index != last && !has_property(element, "item") {
+            issues.push(item_issue(
+                Severity::Error,
+                item,
+                "itemListElement",
+                format!("Breadcrumb {} needs an 'item' URL", index + 1),
+            ));
+        }
+    }
+}
+
+/// A property is present when it is not null, an empty string or an empty list
+fn has_property(properties: &Map<String, Value>, name: &str) -> bool {
+    match properties.get(name) {
+        None | Some(Value::Null) => false,
+        Some(Value::String(s)) => !s.trim().is_empty(),
+        Some(Value::Array(a)) => !a.is_empty(),
+        Some(_) => true,
+    }
+}
+
+/// Treat a single value and a list of values alike
+fn values(value: Option<&Value>) -> Vec<&Value> {
+    match value {
+        Some(Value::Array(items)) => items.iter().collect(),
+        Some(Value::Null) | None => Vec::new(),
+        Some(other) => vec![other],
+    }
+}
+
+fn is_iso8601(value: &str) -> bool {
+    chrono::DateTime::parse_from_rfc3339(value).is_ok()
+        || chrono::NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S").is_ok()
+        || chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok()
+}
+
+/// Strip the vocabulary prefix from a type, e.g. `https://schema.org/Article`
+fn short_type(value: &str) -> String {
+    value
+        .trim_end_matches('/')
+        .rsplit(['/', '#'])
+        .next()
+        .unwrap_or(value)
+        .to_string()
+}
+
+fn collect_json_ld(value: &Value, items: &mut Vec<StructuredItem>) {
+    match value {
+        Value::Array(values) => {
+            for value in values {
+                collect_json_ld(value, items);
+            }
+        }
+        Value::Object(object) => {
+            if let Some(graph) = object.get("@graph") {
+                collect_json_ld(graph, items);
+            }
+            // An item with several types is checked against each of them
+            for item_type in values(object.get("@type")) {
+                if let Some(item_type) = item_type.as_str() {
+                    items.push(StructuredItem {
+                        source: StructuredDataSource::JsonLd,
+                        item_type: short_type(item_type),
+                        properties: object.clone(),
+                    });
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn microdata_item(scope: ElementRef) -> Option<StructuredItem> {
+    let item_type = scope.value().attr("itemtype")?;
+    let mut properties = Map::new();
+    collect_microdata_properties(scope, &mut properties);
+
+    Some(StructuredItem {
+        source: StructuredDataSource::Microdata,
+        item_type: short_type(item_type.split_whitespace().next().unwrap_or(item_type)),
+        properties,
+    })
+}
+
+/// Collect the `itemprop`s belonging to an item, stopping at nested items
+fn collect_microdata_properties(element: ElementRef, properties: &mut Map<String, Value>) {
+    for child in element.children().filter_map(ElementRef::wrap) {
+        let nested = child.value().attr("itemscope").is_some();
+
+        if let Some(names) = child.value().attr("itemprop") {
+            let value = if nested {
+                let mut nested_properties = Map::new();
+                collect_microdata_properties(child, &mut nested_properties);
+                if let Some(item_type) = child.value().attr("itemtype") {
+                    nested_properties
+                        .insert("@type".to_string(), Value::String(short_type(item_type)));
+                }
+                Value::Object(nested_properties)
+            } else {
+                Value::String(microdata_value(child))
+            };
+
+            for name in names.split_whitespace() {
+                match properties.get_mut(name) {
+                    Some(Value::Array(existing)) => existing.push(value.clone()),
+                    Some(existing) => {
+                        let first = existing.take();
+                        *existing = Value::Array(vec![first, value.clone()]);
+                    }
+                    None => {
+                        properties.insert(name.to_string(), value.clone());
+                    }
+                }
+            }
+        }
+
+        if !nested {
+            collect_microdata_properties(child, properties);
+        }
+    }
+}
+
+fn microdata_value(element: ElementRef) -> String {
+    let el = element.value();
+    let attr = match el.name() {
+        "meta" => el.attr("content"),
+        "a" | "link" | "area" => el.attr("href"),
+        "img" | "audio" | "video" | "source" | "iframe" | "embed" => el.attr("src"),
+        "time" => el.attr("datetime"),
+        "data" | "meter" => el.attr("value"),
+        _ => el.attr("content"),
+    };
+
+    attr.map(str::to_string)
+        .unwrap_or_else(|| element.text().collect::<String>().trim().to_string())
+}
+
+/// Validation results for one rendered page
+#[derive(Debug, Clone, Serialize)]
+pub struct PageStructuredData {
+    pub url: String,
+    pub template: String,
+    pub post_id: Option<String>,
+    pub report: StructuredDataReport,
+}
+
+/// Error and warning counts for one template
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TemplateStructuredDataSummary {
+    pub pages: usize,
+    pub failing_pages: usize,
+    pub errors: usize,
+    pub warnings: usize,
+}
+
+/// Site-wide structured data audit, reported per page and per template
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StructuredDataAudit {
+    pub pages: Vec<PageStructuredData>,
+    pub templates: BTreeMap<String, TemplateStructuredDataSummary>,
+    pub errors: usize,
+    pub warnings: usize,
+    pub passed: bool,
+}
+
+impl StructuredDataAudit {
+    pub fn new() -> Self {
+        Self {
+            passed: true,
+            ..Default::default()
+        }
+    }
+
+    /// Add a validated page
+    pub fn add_page(&mut self, page: PageStructuredData) {
+        let summary = self.templates.entry(page.template.clone()).or_default();
+        summary.pages += 1;
+        summary.errors += page.report.errors;
+        summary.warnings += page.report.warnings;
+        if !page.report.passed {
+            summary.failing_pages += 1;
+        }
+
+        self.errors += page.report.errors;
+        self.warnings += page.report.warnings;
+        self.passed &= page.report.passed;
+        self.pages.push(page);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_ld_article_and_breadcrumbs() {
+        let html = r#"<html><head>
+            <script type="application/ld+json">
+            {"@context": "https://schema.org", "@graph": [
+                {"@type": "BlogPosting", "headline": "Hello", "datePublished": "May 5",
+                 "author": {"@type": "Person", "name": "Ann"}},
+                {"@type": "BreadcrumbList", "itemListElement": [
+                    {"@type": "ListItem", "position": 1, "name": "Home", "item": "https://example.com/"},
+                    {"@type": "ListItem", "name": "News", "item": "https://example.com/news"},
+                    {"@type": "ListItem", "position": 3, "name": "Hello"}
+                ]}
+            ]}
+            </script>
+            <script type="application/ld+json">{ not json</script>
+        </head></html>"#;
+
+        let report = StructuredDataValidator::new().validate(html);
+        assert_eq!(report.item_types, vec!["BlogPosting", "BreadcrumbList"]);
+        assert!(!report.passed);
+
+        let messages: Vec<&str> = report.issues.iter().map(|i| i.message.as_str()).collect();
+        assert!(messages.iter().any(|m| m.contains("not valid JSON")));
+        assert!(messages.contains(&"'May 5' is not an ISO 8601 date"));
+        assert!(messages.contains(&"Breadcrumb 2 needs an integer 'position'"));
+        assert!(messages.contains(&"Missing recommended property 'image'"));
+        // The current page may omit its URL
+        assert!(!messages.contains(&"Breadcrumb 3 needs an 'item' URL"));
+        assert_eq!(report.errors, 3);
+    }
+
+    #[test]
+    fn test_json_ld_event() {
+        let html = r#"<script type="application/ld+json">
+            {"@context": "https://schema.org", "@type": "Event", "name": "Meetup",
+             "startDate": "2025-06-01T19:00:00+02:00", "endDate": "June 1",
+             "eventStatus": "https://schema.org/EventScheduled",
+             "location": [
+                {"@type": "Place", "name": "Hall"},
+                {"@type": "VirtualLocation", "url": "https://example.com/live"}
+             ]}
+            </script>"#;
+
+        let report = StructuredDataValidator::new().validate(html);
+        assert_eq!(report.item_types, vec!["Event"]);
+        let messages: Vec<&str> = report.issues.iter().map(|i| i.message.as_str()).collect();
+        assert!(messages.contains(&"'June 1' is not an ISO 8601 date"));
+        assert!(messages.contains(&"Event location needs an 'address'"));
+        assert!(!messages.contains(&"Virtual location needs a 'url'"));
+        assert_eq!(report.errors, 2);
+    }
+
+    #[test]
+    fn test_microdata_product() {
+        let html = r#"<div itemscope itemtype="https://schema.org/Product">
+            <h1 itemprop="name">Widget</h1>
+            <img itemprop="image" src="/widget.png">
+            <div itemprop="offers" itemscope itemtype="https://schema.org/Offer">
+                <meta itemprop="priceCurrency" content="USD">
+                <span itemprop="name">Not the product name</span>
+            </div>
+        </div>"#;
+
+        let validator = StructuredDataValidator::new();
+        let (items, _) = validator.extract(html);
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].source, StructuredDataSource::Microdata);
+        assert_eq!(items[0].properties["name"], "Widget");
+        assert_eq!(items[0].properties["image"], "/widget.png");
+        assert_eq!(items[0].properties["offers"]["@type"], "Offer");
+
+        let report = validator.validate(html);
+        assert_eq!(report.errors, 1);
+        assert_eq!(
+            report.issues[report.issues.len() - 1].message,
+            "Offer needs 'price' and 'priceCurrency'"
+        );
+    }
+
+    #[test]
+    fn test_audit_groups_by_template() {
+        let validator = StructuredDataValidator::new();
+        let good = r#"<script type="application/ld+json">
+            {"@type": "Article", "headline": "A", "author": "Ann",
+             "datePublished": "2024-05-01", "dateModified": "2024-05-02T10:00:00Z",
+             "image": "/a.png", "publisher": "Site"}</script>"#;
+        let bad = r#"<script type="application/ld+json">{"@type": "Article"}</script>"#;
+
+        let mut audit = StructuredDataAudit::new();
+        for (url, html) in [("/post/a", good), ("/post/b", bad)] {
+            audit.add_page(PageStructuredData {
+                url: url.to_string(),
+                template: "single".to_string(),
+                post_id: None,
+                report: validator.validate(html),
+            });
+        }
+
+        assert!(!audit.passed);
+        assert_eq!(audit.errors, 3);
+        let single = &audit.templates["single"];
+        assert_eq!((single.pages, single.failing_pages), (2, 1));
+    }
+}
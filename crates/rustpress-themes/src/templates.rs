@@ -70,6 +70,8 @@ pub struct QueryContext {
     pub is_search: bool,
     pub is_404: bool,
     pub is_attachment: bool,
+    /// Feed variant of the queried archive (RSS instead of HTML)
+    pub is_feed: bool,
     pub post_type: Option<String>,
     pub post_id: Option<i64>,
     pub post_slug: Option<String>,
@@ -79,6 +81,9 @@ pub struct QueryContext {
     pub author_id: Option<i64>,
     pub author_slug: Option<String>,
     pub page_template: Option<String>,
+    pub year: Option<i32>,
+    pub month: Option<u32>,
+    pub day: Option<u32>,
 }
 
 impl TemplateHierarchy {
@@ -274,6 +279,15 @@ impl TemplateHierarchy {
         }
         // Date archive
         else if query.is_date {
+            if let Some(year) = query.year {
+                if let Some(month) = query.month {
+                    if let Some(day) = query.day {
+                        hierarchy.push(format!("date-{}-{:02}-{:02}", year, month, day));
+                    }
+                    hierarchy.push(format!("date-{}-{:02}", year, month));
+                }
+                hierarchy.push(format!("date-{}", year));
+            }
             hierarchy.push("date".to_string());
             hierarchy.push("archive".to_string());
         }
@@ -285,8 +299,18 @@ impl TemplateHierarchy {
             hierarchy.push("archive".to_string());
         }
 
-        // Always fall back to index
-        hierarchy.push("index".to_string());
+        // Feeds prefix the archive hierarchy with `feed-` and end at `feed`;
+        // they never fall back to the HTML index
+        if query.is_feed {
+            hierarchy = hierarchy
+                .iter()
+                .map(|template| format!("feed-{}", template))
+                .collect();
+            hierarchy.push("feed".to_string());
+        } else {
+            // Always fall back to index
+            hierarchy.push("index".to_string());
+        }
 
         // Apply custom overrides
         for template in &hierarchy {
@@ -538,6 +562,12 @@ impl TemplateEngine {
         Ok(())
     }
 
+    /// Register an additional template function, e.g. one provided by the
+    /// host application
+    pub fn register_function<F: tera::Function + 'static>(&self, name: &str, function: F) {
+        self.tera.write().register_function(name, function);
+    }
+
     /// Set global context value
     pub fn set_global(&self, key: &str, value: impl Serialize) {
         let mut context = self.global_context.write();
@@ -845,6 +875,76 @@ mod tests {
         assert_eq!(result[1], "home");
     }
 
+    #[test]
+    fn test_hierarchy_post_type_archive() {
+        let hierarchy = TemplateHierarchy::new();
+        let archive = QueryContext {
+            is_archive: true,
+            post_type: Some("event".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            hierarchy.resolve(&archive),
+            vec!["archive-event", "archive", "index"]
+        );
+
+        let single = QueryContext {
+            is_single: true,
+            post_type: Some("event".to_string()),
+            post_slug: Some("meetup".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            hierarchy.resolve(&single),
+            vec![
+                "single-event-meetup",
+                "single-event",
+                "single",
+                "singular",
+                "index"
+            ]
+        );
+        assert_eq!(
+            hierarchy.detect_template_type("archive-event"),
+            TemplateType::Archive
+        );
+    }
+
+    #[test]
+    fn test_hierarchy_date_and_feed() {
+        let hierarchy = TemplateHierarchy::new();
+        let query = QueryContext {
+            is_date: true,
+            is_archive: true,
+            year: Some(2024),
+            month: Some(5),
+            ..Default::default()
+        };
+
+        let result = hierarchy.resolve(&query);
+        assert_eq!(
+            result,
+            vec!["date-2024-05", "date-2024", "date", "archive", "index"]
+        );
+
+        let feed = QueryContext {
+            is_category: true,
+            is_archive: true,
+            is_feed: true,
+            term_slug: Some("news".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            hierarchy.resolve(&feed),
+            vec![
+                "feed-category-news",
+                "feed-category",
+                "feed-archive",
+                "feed"
+            ]
+        );
+    }
+
     #[test]
     fn test_template_part_area_detection() {
         let manager = TemplatePartManager::new(PathBuf::from("/tmp/parts"));
@@ -0,0 +1,22 @@
+-- ============================================
+-- Migration: 00027_sagas.sql
+-- Description: Persisted saga state for multi-step operations
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS sagas (
+    id UUID PRIMARY KEY,
+    saga_type VARCHAR(255) NOT NULL,
+    tenant_id UUID,
+    status VARCHAR(20) NOT NULL DEFAULT 'running' CHECK (status IN ('running', 'completed', 'compensating', 'compensated', 'failed')),
+    current_step INT NOT NULL DEFAULT 0,
+    completed_steps JSONB NOT NULL DEFAULT '[]',
+    context JSONB NOT NULL DEFAULT '{}',
+    error TEXT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_sagas_active ON sagas(status) WHERE status IN ('running', 'compensating');
+CREATE INDEX IF NOT EXISTS idx_sagas_type ON sagas(saga_type, created_at DESC);
+
+COMMENT ON TABLE sagas IS 'Saga coordinator state; active rows are resumed on startup';
@@ -0,0 +1,8 @@
+-- ============================================
+-- Migration: 00029_user_profiles.sql
+-- Description: Profile columns read by public author pages; other
+--              profile fields live in users.meta under "profile"
+-- ============================================
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS bio TEXT;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS website VARCHAR(500);
@@ -0,0 +1,25 @@
+-- ============================================
+-- Migration: 00030_saved_views.sql
+-- Description: Named filter/sort/column configurations for admin lists;
+--              per-user defaults and ordering live in users.meta under
+--              "list_views"
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS saved_views (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    owner_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    resource VARCHAR(20) NOT NULL CHECK (resource IN ('posts', 'comments', 'media')),
+    name VARCHAR(100) NOT NULL,
+    filter TEXT,
+    sort_by VARCHAR(50),
+    sort_order VARCHAR(4) CHECK (sort_order IN ('asc', 'desc')),
+    columns JSONB NOT NULL DEFAULT '[]',
+    shared_roles JSONB NOT NULL DEFAULT '[]',
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    UNIQUE (owner_id, resource, name)
+);
+
+CREATE INDEX IF NOT EXISTS idx_saved_views_shared ON saved_views USING GIN (shared_roles);
+
+COMMENT ON TABLE saved_views IS 'Saved admin list views, visible to their owner and the roles in shared_roles';
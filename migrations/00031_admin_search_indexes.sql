@@ -0,0 +1,27 @@
+-- ============================================
+-- Migration: 00031_admin_search_indexes.sql
+-- Description: Prefix indexes for the admin command palette search;
+--              lower(col) text_pattern_ops serves `LIKE 'term%'` under any
+--              collation. Also adds the posts.deleted_at column the trash
+--              code relies on.
+-- ============================================
+
+ALTER TABLE posts ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMP WITH TIME ZONE;
+
+CREATE INDEX IF NOT EXISTS idx_posts_admin_search
+    ON posts (post_type, lower(title) text_pattern_ops);
+
+CREATE INDEX IF NOT EXISTS idx_media_admin_search_title
+    ON media (lower(title) text_pattern_ops);
+CREATE INDEX IF NOT EXISTS idx_media_admin_search_filename
+    ON media (lower(original_filename) text_pattern_ops);
+
+CREATE INDEX IF NOT EXISTS idx_users_admin_search_username
+    ON users (lower(username) text_pattern_ops);
+CREATE INDEX IF NOT EXISTS idx_users_admin_search_email
+    ON users (lower(email) text_pattern_ops);
+CREATE INDEX IF NOT EXISTS idx_users_admin_search_display_name
+    ON users (lower(display_name) text_pattern_ops);
+
+CREATE INDEX IF NOT EXISTS idx_settings_admin_search
+    ON settings (lower(key) text_pattern_ops);
@@ -0,0 +1,48 @@
+-- ============================================
+-- Migration: 00032_public_api.sql
+-- Description: Keys for the read-only public API tier and the rollup
+--              tables its per-key usage is flushed into
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS public_api_keys (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(100) NOT NULL,
+    key_prefix VARCHAR(16) NOT NULL,
+    key_hash CHAR(64) NOT NULL UNIQUE,
+    owner_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    requests_per_minute INTEGER NOT NULL DEFAULT 60 CHECK (requests_per_minute > 0),
+    daily_quota INTEGER CHECK (daily_quota IS NULL OR daily_quota > 0),
+    allowed_origins JSONB NOT NULL DEFAULT '[]',
+    last_used_at TIMESTAMP WITH TIME ZONE,
+    revoked_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS public_api_usage_hourly (
+    key_id UUID NOT NULL REFERENCES public_api_keys(id) ON DELETE CASCADE,
+    hour TIMESTAMP WITH TIME ZONE NOT NULL,
+    requests BIGINT NOT NULL DEFAULT 0,
+    bytes_out BIGINT NOT NULL DEFAULT 0,
+    errors BIGINT NOT NULL DEFAULT 0,
+    rate_limited BIGINT NOT NULL DEFAULT 0,
+    cache_hits BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (key_id, hour)
+);
+
+CREATE INDEX IF NOT EXISTS idx_public_api_usage_hourly_hour ON public_api_usage_hourly(hour);
+
+CREATE TABLE IF NOT EXISTS public_api_endpoint_usage_daily (
+    key_id UUID NOT NULL REFERENCES public_api_keys(id) ON DELETE CASCADE,
+    day DATE NOT NULL,
+    endpoint VARCHAR(200) NOT NULL,
+    requests BIGINT NOT NULL DEFAULT 0,
+    bytes_out BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (key_id, day, endpoint)
+);
+
+CREATE INDEX IF NOT EXISTS idx_public_api_endpoint_usage_day ON public_api_endpoint_usage_daily(day);
+
+COMMENT ON TABLE public_api_keys IS 'Read-only public API keys; only the SHA-256 hash of the secret is stored';
+COMMENT ON TABLE public_api_usage_hourly IS 'Public API requests, bandwidth, errors and rate limiting per key and hour';
+COMMENT ON TABLE public_api_endpoint_usage_daily IS 'Public API requests and bandwidth per key, day and route';
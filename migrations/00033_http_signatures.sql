@@ -0,0 +1,27 @@
+-- ============================================
+-- Migration: 00033_http_signatures.sql
+-- Description: Keys for HTTP message signatures (RFC 9421): public keys of
+--              trusted integrations and the site's own signing keys
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS http_signature_keys (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    key_id VARCHAR(255) NOT NULL UNIQUE,
+    name VARCHAR(100) NOT NULL,
+    direction VARCHAR(16) NOT NULL CHECK (direction IN ('inbound', 'outbound')),
+    algorithm VARCHAR(32) NOT NULL,
+    public_key BYTEA NOT NULL,
+    private_key BYTEA,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    expires_at TIMESTAMP WITH TIME ZONE,
+    last_used_at TIMESTAMP WITH TIME ZONE,
+    revoked_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    CHECK (direction = 'outbound' OR private_key IS NULL)
+);
+
+CREATE INDEX IF NOT EXISTS idx_http_signature_keys_direction
+    ON http_signature_keys(direction, created_at DESC) WHERE revoked_at IS NULL;
+
+COMMENT ON TABLE http_signature_keys IS 'HTTP message signature keys; outbound private keys are stored sealed with a key derived from the site secret';
@@ -0,0 +1,24 @@
+-- ============================================
+-- Migration: 00034_content_sanitization.sql
+-- Description: Reports of the tags, attributes and comments the content
+--              sanitization policy stripped from stored HTML
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS content_sanitization_reports (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    source VARCHAR(20) NOT NULL CHECK (source IN ('post', 'comment', 'legacy')),
+    subject_id UUID,
+    user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    policy VARCHAR(100) NOT NULL,
+    removed_tags JSONB NOT NULL DEFAULT '{}',
+    removed_attributes JSONB NOT NULL DEFAULT '{}',
+    removed_comments INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_content_sanitization_reports_subject
+    ON content_sanitization_reports(source, subject_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_content_sanitization_reports_created
+    ON content_sanitization_reports(created_at DESC);
+
+COMMENT ON TABLE content_sanitization_reports IS 'What the sanitization policy stripped from saved posts, comments and legacy HTML';
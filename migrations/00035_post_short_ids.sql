@@ -0,0 +1,11 @@
+-- ============================================
+-- Migration: 00035_post_short_ids.sql
+-- Description: Numeric post ids for `/?p=<id>` shortlinks; existing posts
+--              are numbered when the column is added
+-- ============================================
+
+ALTER TABLE posts ADD COLUMN IF NOT EXISTS short_id BIGSERIAL;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_posts_short_id ON posts(short_id);
+
+COMMENT ON COLUMN posts.short_id IS 'Numeric id used by /?p= shortlinks';
@@ -0,0 +1,17 @@
+-- ============================================
+-- Migration: 00036_extension_allowlists.sql
+-- Description: Theme and plugin allowlists for the network, each tenant
+--              and each site; a NULL list leaves that kind unrestricted
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS extension_allowlists (
+    scope VARCHAR(20) NOT NULL CHECK (scope IN ('network', 'tenant', 'site')),
+    scope_id VARCHAR(100) NOT NULL DEFAULT '',
+    themes JSONB,
+    plugins JSONB,
+    updated_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (scope, scope_id)
+);
+
+COMMENT ON TABLE extension_allowlists IS 'Themes and plugins each network, tenant or site may install and activate';
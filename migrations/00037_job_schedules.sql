@@ -0,0 +1,23 @@
+-- ============================================
+-- Migration: 00037_job_schedules.sql
+-- Description: Cron schedules defined by site owners, with the time each
+--              last ran and is next due so missed runs can be caught up
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS job_schedules (
+    name VARCHAR(255) PRIMARY KEY,
+    expression VARCHAR(255) NOT NULL,
+    timezone VARCHAR(64) NOT NULL DEFAULT 'UTC',
+    catch_up VARCHAR(20) NOT NULL DEFAULT 'run_once' CHECK (catch_up IN ('skip', 'run_once', 'run_all')),
+    catch_up_max INT,
+    job_type VARCHAR(255) NOT NULL,
+    queue VARCHAR(100) NOT NULL DEFAULT 'default',
+    payload JSONB NOT NULL DEFAULT '{}',
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    last_run_at TIMESTAMP WITH TIME ZONE,
+    next_run_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON TABLE job_schedules IS 'Cron schedules loaded by the job scheduler on startup';
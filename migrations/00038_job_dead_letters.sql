@@ -0,0 +1,23 @@
+-- ============================================
+-- Migration: 00038_job_dead_letters.sql
+-- Description: Jobs that ran out of retry attempts, kept for an
+--              administrator to inspect, requeue or discard
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS job_dead_letters (
+    id UUID PRIMARY KEY,
+    tenant_id UUID,
+    queue VARCHAR(100) NOT NULL,
+    job_type VARCHAR(255) NOT NULL,
+    payload JSONB NOT NULL DEFAULT '{}',
+    priority INT NOT NULL DEFAULT 0,
+    attempts INT NOT NULL DEFAULT 0,
+    max_attempts INT NOT NULL DEFAULT 3,
+    error TEXT NOT NULL,
+    job_created_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    failed_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_job_dead_letters_queue ON job_dead_letters(queue, failed_at DESC);
+
+COMMENT ON TABLE job_dead_letters IS 'Failed jobs moved out of the jobs table once their retry policy gives up';
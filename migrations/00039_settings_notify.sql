@@ -0,0 +1,70 @@
+-- ============================================
+-- Migration: 00039_settings_notify.sql
+-- Description: Announce settings, site option and extension allowlist
+--              changes on the rustpress_settings channel so every node
+--              drops its cached copy
+-- ============================================
+
+-- Payloads are `setting:<key>`, `option:<option_name>` and `allowlists`.
+-- pg_notify is transactional: listeners only hear about committed changes.
+
+CREATE OR REPLACE FUNCTION notify_setting_change()
+RETURNS TRIGGER AS $$
+DECLARE
+    changed_key TEXT;
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        changed_key := OLD.key;
+    ELSE
+        changed_key := NEW.key;
+    END IF;
+    PERFORM pg_notify('rustpress_settings', 'setting:' || changed_key);
+    IF TG_OP = 'UPDATE' AND OLD.key IS DISTINCT FROM NEW.key THEN
+        PERFORM pg_notify('rustpress_settings', 'setting:' || OLD.key);
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trigger_notify_setting_change
+    AFTER INSERT OR UPDATE OR DELETE ON settings
+    FOR EACH ROW
+    EXECUTE FUNCTION notify_setting_change();
+
+CREATE OR REPLACE FUNCTION notify_option_change()
+RETURNS TRIGGER AS $$
+DECLARE
+    changed_name TEXT;
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        changed_name := OLD.option_name;
+    ELSE
+        changed_name := NEW.option_name;
+    END IF;
+    PERFORM pg_notify('rustpress_settings', 'option:' || changed_name);
+    IF TG_OP = 'UPDATE' AND OLD.option_name IS DISTINCT FROM NEW.option_name THEN
+        PERFORM pg_notify('rustpress_settings', 'option:' || OLD.option_name);
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trigger_notify_option_change
+    AFTER INSERT OR UPDATE OR DELETE ON options
+    FOR EACH ROW
+    EXECUTE FUNCTION notify_option_change();
+
+-- Allowlists are cached as one map, so a single statement-level notice
+-- covers bulk updates
+CREATE OR REPLACE FUNCTION notify_allowlists_change()
+RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM pg_notify('rustpress_settings', 'allowlists');
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trigger_notify_allowlists_change
+    AFTER INSERT OR UPDATE OR DELETE ON extension_allowlists
+    FOR EACH STATEMENT
+    EXECUTE FUNCTION notify_allowlists_change();
@@ -0,0 +1,92 @@
+-- ============================================
+-- Migration: 00040_media_library.sql
+-- Description: Folder details and counts, tags and usage tracking for
+--              the media library
+-- ============================================
+
+-- Folder details the library UI shows
+ALTER TABLE media_folders ADD COLUMN IF NOT EXISTS slug VARCHAR(255) NOT NULL DEFAULT '';
+ALTER TABLE media_folders ADD COLUMN IF NOT EXISTS description TEXT;
+ALTER TABLE media_folders ADD COLUMN IF NOT EXISTS user_id UUID REFERENCES users(id) ON DELETE SET NULL;
+ALTER TABLE media_folders ADD COLUMN IF NOT EXISTS color VARCHAR(20) NOT NULL DEFAULT '#6366f1';
+ALTER TABLE media_folders ADD COLUMN IF NOT EXISTS icon VARCHAR(50) NOT NULL DEFAULT 'folder';
+ALTER TABLE media_folders ADD COLUMN IF NOT EXISTS item_count INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE media_folders ADD COLUMN IF NOT EXISTS total_size BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE media_folders ADD COLUMN IF NOT EXISTS is_system BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE media_folders ADD COLUMN IF NOT EXISTS sort_order INTEGER NOT NULL DEFAULT 0;
+
+-- Free-form tags, searched and bulk edited from the library
+ALTER TABLE media ADD COLUMN IF NOT EXISTS tags TEXT[] NOT NULL DEFAULT '{}';
+
+CREATE INDEX IF NOT EXISTS idx_media_folder_id ON media(folder_id);
+CREATE INDEX IF NOT EXISTS idx_media_tags ON media USING GIN (tags);
+
+-- Where each media item is used; `context` tells a featured image from an
+-- image in the content
+CREATE TABLE IF NOT EXISTS media_usage (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    media_id UUID NOT NULL REFERENCES media(id) ON DELETE CASCADE,
+    entity_type VARCHAR(50) NOT NULL,
+    entity_id UUID NOT NULL,
+    context VARCHAR(50) NOT NULL DEFAULT 'content',
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    UNIQUE (media_id, entity_type, entity_id, context)
+);
+
+CREATE INDEX IF NOT EXISTS idx_media_usage_entity ON media_usage(entity_type, entity_id);
+
+COMMENT ON TABLE media_usage IS 'Posts, pages and other content that reference each media item';
+
+-- ============================================
+-- TRIGGERS
+-- ============================================
+
+-- Keep folder item counts and sizes in step with the media in them
+CREATE OR REPLACE FUNCTION refresh_media_folder_stats(folder UUID)
+RETURNS VOID AS $$
+BEGIN
+    IF folder IS NULL THEN
+        RETURN;
+    END IF;
+    UPDATE media_folders SET
+        item_count = stats.item_count,
+        total_size = stats.total_size
+    FROM (
+        SELECT COUNT(*)::INTEGER AS item_count, COALESCE(SUM(file_size), 0)::BIGINT AS total_size
+        FROM media
+        WHERE folder_id = folder AND deleted_at IS NULL
+    ) stats
+    WHERE id = folder;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION update_media_folder_stats()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP IN ('UPDATE', 'DELETE') THEN
+        PERFORM refresh_media_folder_stats(OLD.folder_id);
+    END IF;
+    IF TG_OP = 'INSERT'
+        OR (TG_OP = 'UPDATE' AND NEW.folder_id IS DISTINCT FROM OLD.folder_id) THEN
+        PERFORM refresh_media_folder_stats(NEW.folder_id);
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trigger_update_media_folder_stats
+    AFTER INSERT OR DELETE OR UPDATE OF folder_id, file_size, deleted_at ON media
+    FOR EACH ROW
+    EXECUTE FUNCTION update_media_folder_stats();
+
+-- Counts for folders that already hold media
+UPDATE media_folders SET
+    item_count = stats.item_count,
+    total_size = stats.total_size
+FROM (
+    SELECT folder_id, COUNT(*)::INTEGER AS item_count, COALESCE(SUM(file_size), 0)::BIGINT AS total_size
+    FROM media
+    WHERE folder_id IS NOT NULL AND deleted_at IS NULL
+    GROUP BY folder_id
+) stats
+WHERE media_folders.id = stats.folder_id;
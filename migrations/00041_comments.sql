@@ -0,0 +1,187 @@
+-- ============================================
+-- Migration: 00041_comments.sql
+-- Description: Threading, moderation and spam columns for comments,
+--              comment meta, and per-post comment settings
+-- ============================================
+
+-- Bound by the comment moderation queries; the column itself stays text
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'comment_status') THEN
+        CREATE TYPE comment_status AS ENUM ('pending', 'approved', 'spam', 'trash');
+    END IF;
+END $$;
+
+-- Threading
+ALTER TABLE comments ADD COLUMN IF NOT EXISTS site_id UUID;
+ALTER TABLE comments ADD COLUMN IF NOT EXISTS depth INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE comments ADD COLUMN IF NOT EXISTS replies_count INTEGER NOT NULL DEFAULT 0;
+
+-- Author details; `user_id` replaces `author_id` for registered users
+ALTER TABLE comments ADD COLUMN IF NOT EXISTS user_id UUID REFERENCES users(id) ON DELETE SET NULL;
+ALTER TABLE comments ADD COLUMN IF NOT EXISTS author_ip VARCHAR(45);
+ALTER TABLE comments ADD COLUMN IF NOT EXISTS user_agent TEXT;
+
+-- Rendered content and edits
+ALTER TABLE comments ADD COLUMN IF NOT EXISTS content_html TEXT;
+ALTER TABLE comments ADD COLUMN IF NOT EXISTS is_edited BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE comments ADD COLUMN IF NOT EXISTS edited_at TIMESTAMP WITH TIME ZONE;
+
+-- Moderation and spam detection
+ALTER TABLE comments ADD COLUMN IF NOT EXISTS moderated_by UUID REFERENCES users(id) ON DELETE SET NULL;
+ALTER TABLE comments ADD COLUMN IF NOT EXISTS moderated_at TIMESTAMP WITH TIME ZONE;
+ALTER TABLE comments ADD COLUMN IF NOT EXISTS moderation_note TEXT;
+ALTER TABLE comments ADD COLUMN IF NOT EXISTS spam_score DOUBLE PRECISION;
+ALTER TABLE comments ADD COLUMN IF NOT EXISTS spam_reasons JSONB;
+
+UPDATE comments SET likes_count = 0 WHERE likes_count IS NULL;
+ALTER TABLE comments ALTER COLUMN likes_count SET NOT NULL;
+
+DO $$
+BEGIN
+    IF EXISTS (
+        SELECT 1 FROM information_schema.columns
+        WHERE table_name = 'comments' AND column_name = 'author_id'
+    ) THEN
+        UPDATE comments SET user_id = author_id WHERE user_id IS NULL AND author_id IS NOT NULL;
+    END IF;
+END $$;
+
+CREATE INDEX IF NOT EXISTS idx_comments_parent ON comments(parent_id);
+CREATE INDEX IF NOT EXISTS idx_comments_user ON comments(user_id);
+-- Moderation queue, oldest first
+CREATE INDEX IF NOT EXISTS idx_comments_queue ON comments(status, created_at)
+    WHERE deleted_at IS NULL;
+
+-- Likes by signed-in users; guest likes keep using idx_comment_likes_unique
+CREATE UNIQUE INDEX IF NOT EXISTS idx_comment_likes_user ON comment_likes(comment_id, user_id);
+
+CREATE TABLE IF NOT EXISTS comment_meta (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    comment_id UUID NOT NULL REFERENCES comments(id) ON DELETE CASCADE,
+    meta_key VARCHAR(255) NOT NULL,
+    meta_value JSONB,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    UNIQUE (comment_id, meta_key)
+);
+
+-- Whether a post takes comments at all ('open' or 'closed')
+ALTER TABLE posts ADD COLUMN IF NOT EXISTS comment_status VARCHAR(50) DEFAULT 'open';
+ALTER TABLE posts ADD COLUMN IF NOT EXISTS comment_count INTEGER DEFAULT 0;
+
+-- Per-post overrides of the discussion settings; NULL inherits the
+-- site-wide value
+CREATE TABLE IF NOT EXISTS post_comment_settings (
+    post_id UUID PRIMARY KEY REFERENCES posts(id) ON DELETE CASCADE,
+    require_moderation BOOLEAN,
+    max_depth INTEGER CHECK (max_depth IS NULL OR max_depth >= 1),
+    closes_at TIMESTAMP WITH TIME ZONE,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON TABLE post_comment_settings IS 'Moderation, threading depth and closing time overrides for a post''s comments';
+
+-- ============================================
+-- TRIGGERS
+-- ============================================
+
+-- Replies sit one level below their parent
+CREATE OR REPLACE FUNCTION set_comment_depth()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF NEW.parent_id IS NULL THEN
+        NEW.depth := 0;
+    ELSE
+        SELECT depth + 1 INTO NEW.depth FROM comments WHERE id = NEW.parent_id;
+        NEW.depth := COALESCE(NEW.depth, 0);
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trigger_set_comment_depth ON comments;
+CREATE TRIGGER trigger_set_comment_depth
+    BEFORE INSERT OR UPDATE OF parent_id ON comments
+    FOR EACH ROW
+    EXECUTE FUNCTION set_comment_depth();
+
+-- Reply and post comment counts only include approved, visible comments
+CREATE OR REPLACE FUNCTION refresh_comment_counts(post UUID, parent UUID)
+RETURNS VOID AS $$
+BEGIN
+    IF parent IS NOT NULL THEN
+        UPDATE comments SET replies_count = (
+            SELECT COUNT(*) FROM comments
+            WHERE parent_id = parent AND status = 'approved' AND deleted_at IS NULL
+        )
+        WHERE id = parent;
+    END IF;
+    UPDATE posts SET comment_count = (
+        SELECT COUNT(*) FROM comments
+        WHERE post_id = post AND status = 'approved' AND deleted_at IS NULL
+    )
+    WHERE id = post;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION update_comment_counts()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP IN ('UPDATE', 'DELETE') THEN
+        PERFORM refresh_comment_counts(OLD.post_id, OLD.parent_id);
+    END IF;
+    IF TG_OP = 'INSERT'
+        OR (TG_OP = 'UPDATE' AND (NEW.post_id, NEW.parent_id) IS DISTINCT FROM (OLD.post_id, OLD.parent_id)) THEN
+        PERFORM refresh_comment_counts(NEW.post_id, NEW.parent_id);
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trigger_update_comment_counts ON comments;
+CREATE TRIGGER trigger_update_comment_counts
+    AFTER INSERT OR DELETE OR UPDATE OF status, deleted_at, parent_id, post_id ON comments
+    FOR EACH ROW
+    EXECUTE FUNCTION update_comment_counts();
+
+CREATE OR REPLACE FUNCTION update_comment_likes_count()
+RETURNS TRIGGER AS $$
+DECLARE
+    target UUID := CASE WHEN TG_OP = 'DELETE' THEN OLD.comment_id ELSE NEW.comment_id END;
+BEGIN
+    UPDATE comments SET likes_count = (
+        SELECT COUNT(*) FROM comment_likes WHERE comment_id = target
+    )
+    WHERE id = target;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trigger_update_comment_likes_count ON comment_likes;
+CREATE TRIGGER trigger_update_comment_likes_count
+    AFTER INSERT OR DELETE ON comment_likes
+    FOR EACH ROW
+    EXECUTE FUNCTION update_comment_likes_count();
+
+-- Depths and counts for comments that already exist
+WITH RECURSIVE thread AS (
+    SELECT id, 0 AS depth FROM comments WHERE parent_id IS NULL
+    UNION ALL
+    SELECT c.id, thread.depth + 1 FROM comments c JOIN thread ON c.parent_id = thread.id
+)
+UPDATE comments SET depth = thread.depth FROM thread WHERE comments.id = thread.id;
+
+UPDATE comments SET replies_count = (
+    SELECT COUNT(*) FROM comments r
+    WHERE r.parent_id = comments.id AND r.status = 'approved' AND r.deleted_at IS NULL
+);
+
+UPDATE comments SET likes_count = (
+    SELECT COUNT(*) FROM comment_likes WHERE comment_likes.comment_id = comments.id
+);
+
+UPDATE posts SET comment_count = (
+    SELECT COUNT(*) FROM comments
+    WHERE comments.post_id = posts.id AND comments.status = 'approved' AND comments.deleted_at IS NULL
+);
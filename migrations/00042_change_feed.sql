@@ -0,0 +1,51 @@
+-- ============================================
+-- Migration: 00042_change_feed.sql
+-- Description: Announce content writes on the rustpress_changes channel
+--              so every node can push live updates without polling
+-- ============================================
+
+-- Payloads are small JSON objects:
+--   {"table": "posts", "op": "update", "id": "<uuid>", "tenant_id": "<uuid>"}
+-- `tenant_id` is the row's site_id or tenant_id, if it has one. Identical
+-- notices in one transaction are delivered once, and only after commit.
+
+CREATE OR REPLACE FUNCTION notify_row_change()
+RETURNS TRIGGER AS $$
+DECLARE
+    changed JSONB;
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        changed := to_jsonb(OLD);
+    ELSE
+        changed := to_jsonb(NEW);
+    END IF;
+    PERFORM pg_notify('rustpress_changes', json_build_object(
+        'table', TG_TABLE_NAME,
+        'op', lower(TG_OP),
+        'id', changed->>'id',
+        'tenant_id', COALESCE(changed->>'site_id', changed->>'tenant_id')
+    )::text);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DO $$
+DECLARE
+    watched TEXT;
+BEGIN
+    FOREACH watched IN ARRAY ARRAY[
+        'posts', 'pages', 'comments', 'media', 'media_folders',
+        'categories', 'tags', 'menus', 'menu_items', 'users'
+    ] LOOP
+        IF to_regclass(watched) IS NOT NULL THEN
+            EXECUTE format('DROP TRIGGER IF EXISTS trigger_notify_row_change ON %I', watched);
+            EXECUTE format(
+                'CREATE TRIGGER trigger_notify_row_change
+                    AFTER INSERT OR UPDATE OR DELETE ON %I
+                    FOR EACH ROW
+                    EXECUTE FUNCTION notify_row_change()',
+                watched
+            );
+        END IF;
+    END LOOP;
+END $$;
@@ -0,0 +1,33 @@
+-- ============================================
+-- Migration: 00043_search_documents.sql
+-- Description: Full-text search index for published posts, pages and
+--              media used by the built-in PostgreSQL search backend
+-- ============================================
+
+-- One row per searchable item, kept up to date from the change feed.
+-- `source_updated_at` is the item's own updated_at; older copies never
+-- overwrite newer ones, so every node may index the same change.
+CREATE TABLE IF NOT EXISTS search_documents (
+    id UUID PRIMARY KEY,
+    kind VARCHAR(20) NOT NULL CHECK (kind IN ('post', 'page', 'media')),
+    title TEXT NOT NULL DEFAULT '',
+    excerpt TEXT,
+    body TEXT NOT NULL DEFAULT '',
+    slug VARCHAR(500),
+    published_at TIMESTAMP WITH TIME ZONE,
+    source_updated_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    language REGCONFIG NOT NULL DEFAULT 'english',
+    search_vector TSVECTOR GENERATED ALWAYS AS (
+        setweight(to_tsvector(language, COALESCE(title, '')), 'A') ||
+        setweight(to_tsvector(language, COALESCE(excerpt, '')), 'B') ||
+        setweight(to_tsvector(language, COALESCE(body, '')), 'C')
+    ) STORED,
+    indexed_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_search_documents_vector ON search_documents USING GIN (search_vector);
+CREATE INDEX IF NOT EXISTS idx_search_documents_kind ON search_documents(kind);
+-- Title prefix suggestions
+CREATE INDEX IF NOT EXISTS idx_search_documents_title ON search_documents(lower(title) text_pattern_ops);
+
+COMMENT ON TABLE search_documents IS 'Full-text search index of published posts, pages and media';
@@ -0,0 +1,44 @@
+-- ============================================
+-- Migration: 00044_job_executions.sql
+-- Description: Audit trail of job attempts and per-job-type SLAs
+--              evaluated against it
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS job_executions (
+    id BIGSERIAL PRIMARY KEY,
+    job_id UUID NOT NULL,
+    tenant_id UUID,
+    queue VARCHAR(100) NOT NULL,
+    job_type VARCHAR(255) NOT NULL,
+    attempt INT NOT NULL,
+    -- completed, retried (failed with attempts left) or failed (buried)
+    outcome VARCHAR(20) NOT NULL CHECK (outcome IN ('completed', 'retried', 'failed')),
+    error TEXT,
+    available_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    started_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    finished_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    queue_latency_ms BIGINT NOT NULL,
+    duration_ms BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_job_executions_type ON job_executions(job_type, finished_at DESC);
+CREATE INDEX IF NOT EXISTS idx_job_executions_finished ON job_executions(finished_at);
+CREATE INDEX IF NOT EXISTS idx_job_executions_job ON job_executions(job_id);
+
+COMMENT ON TABLE job_executions IS 'One row per job attempt, recorded by the worker when the attempt ends';
+
+CREATE TABLE IF NOT EXISTS job_slas (
+    job_type VARCHAR(255) PRIMARY KEY,
+    max_duration_secs BIGINT CHECK (max_duration_secs > 0),
+    max_queue_latency_secs BIGINT CHECK (max_queue_latency_secs > 0),
+    max_failure_rate DOUBLE PRECISION CHECK (max_failure_rate >= 0 AND max_failure_rate <= 1),
+    -- Attempts needed in the window before the failure rate is judged
+    min_executions INT NOT NULL DEFAULT 5,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    -- Last time a breach was announced, to hold back repeat alerts
+    alerted_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON TABLE job_slas IS 'Service levels per job type, checked by the evaluate_job_slas job';
@@ -0,0 +1,23 @@
+-- ============================================
+-- Migration: 00045_user_api_keys.sql
+-- Description: Scoped API keys that act as the user who created them, for
+--              scripts and running the CLI against a live site
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS user_api_keys (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name VARCHAR(100) NOT NULL,
+    key_prefix VARCHAR(16) NOT NULL,
+    key_hash CHAR(64) NOT NULL UNIQUE,
+    scopes JSONB NOT NULL DEFAULT '["*:*"]',
+    expires_at TIMESTAMP WITH TIME ZONE,
+    last_used_at TIMESTAMP WITH TIME ZONE,
+    revoked_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_user_api_keys_user ON user_api_keys(user_id, created_at DESC);
+
+COMMENT ON TABLE user_api_keys IS 'Scoped API keys acting as their owner; only the SHA-256 hash of the secret is stored';
+COMMENT ON COLUMN user_api_keys.scopes IS 'resource:action pairs such as posts:read or *:write';
@@ -0,0 +1,27 @@
+-- ============================================
+-- Migration: 00046_user_imports.sql
+-- Description: Bulk user import runs with their progress, so admins and the
+--              CLI can follow an import while it is processed
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS user_imports (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    started_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'running',
+    dry_run BOOLEAN NOT NULL DEFAULT FALSE,
+    total INTEGER NOT NULL DEFAULT 0,
+    processed INTEGER NOT NULL DEFAULT 0,
+    created INTEGER NOT NULL DEFAULT 0,
+    updated INTEGER NOT NULL DEFAULT 0,
+    skipped INTEGER NOT NULL DEFAULT 0,
+    failed INTEGER NOT NULL DEFAULT 0,
+    invited INTEGER NOT NULL DEFAULT 0,
+    errors JSONB NOT NULL DEFAULT '[]',
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    finished_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX IF NOT EXISTS idx_user_imports_created ON user_imports(created_at DESC);
+
+COMMENT ON TABLE user_imports IS 'Bulk user import runs; rows are processed in the background and counters updated as they go';
+COMMENT ON COLUMN user_imports.errors IS 'Per-row failures as {row, email, message}';
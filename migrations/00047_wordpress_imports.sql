@@ -0,0 +1,53 @@
+-- ============================================
+-- Migration: 00047_wordpress_imports.sql
+-- Description: WordPress (WXR) import runs with their progress, and the
+--              redirects that keep old WordPress URLs working
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS wordpress_imports (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    started_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'running',
+    dry_run BOOLEAN NOT NULL DEFAULT FALSE,
+    source_url VARCHAR(500),
+    total INTEGER NOT NULL DEFAULT 0,
+    processed INTEGER NOT NULL DEFAULT 0,
+    authors INTEGER NOT NULL DEFAULT 0,
+    categories INTEGER NOT NULL DEFAULT 0,
+    tags INTEGER NOT NULL DEFAULT 0,
+    posts INTEGER NOT NULL DEFAULT 0,
+    pages INTEGER NOT NULL DEFAULT 0,
+    media INTEGER NOT NULL DEFAULT 0,
+    comments INTEGER NOT NULL DEFAULT 0,
+    redirects INTEGER NOT NULL DEFAULT 0,
+    skipped INTEGER NOT NULL DEFAULT 0,
+    failed INTEGER NOT NULL DEFAULT 0,
+    errors JSONB NOT NULL DEFAULT '[]',
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    finished_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX IF NOT EXISTS idx_wordpress_imports_created ON wordpress_imports(created_at DESC);
+
+COMMENT ON TABLE wordpress_imports IS 'WXR import runs; items are processed in the background and counters updated as they go';
+COMMENT ON COLUMN wordpress_imports.total IS 'Posts, pages and attachments in the file';
+COMMENT ON COLUMN wordpress_imports.errors IS 'Per-item failures as {item, title, message}';
+
+-- Permanent redirects from paths that no longer exist, answered before the
+-- 404 page
+CREATE TABLE IF NOT EXISTS redirects (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    source_path VARCHAR(768) NOT NULL UNIQUE,
+    target VARCHAR(2048) NOT NULL,
+    status_code SMALLINT NOT NULL DEFAULT 301,
+    origin VARCHAR(50) NOT NULL DEFAULT 'manual',
+    import_id UUID REFERENCES wordpress_imports(id) ON DELETE SET NULL,
+    hits BIGINT NOT NULL DEFAULT 0,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_redirects_import ON redirects(import_id);
+
+COMMENT ON TABLE redirects IS 'Old paths redirected to their new location, e.g. WordPress permalinks after an import';
+COMMENT ON COLUMN redirects.source_path IS 'Decoded path without trailing slash or query string';
+COMMENT ON COLUMN redirects.target IS 'Site path or absolute URL';
@@ -0,0 +1,40 @@
+-- ============================================
+-- Migration: 00048_user_groups.sql
+-- Description: User groups (teams) granting roles and capabilities to their
+--              members and owning content sections
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS user_groups (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(100) NOT NULL,
+    slug VARCHAR(100) NOT NULL UNIQUE,
+    description TEXT,
+    roles JSONB NOT NULL DEFAULT '[]',
+    capabilities JSONB NOT NULL DEFAULT '[]',
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS user_group_members (
+    group_id UUID NOT NULL REFERENCES user_groups(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    is_manager BOOLEAN NOT NULL DEFAULT FALSE,
+    added_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (group_id, user_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_user_group_members_user ON user_group_members(user_id);
+
+CREATE TABLE IF NOT EXISTS user_group_sections (
+    group_id UUID NOT NULL REFERENCES user_groups(id) ON DELETE CASCADE,
+    category_id UUID NOT NULL REFERENCES categories(id) ON DELETE CASCADE,
+    added_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (group_id, category_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_user_group_sections_category ON user_group_sections(category_id);
+
+COMMENT ON TABLE user_groups IS 'Teams of users; members gain the group''s roles and capabilities';
+COMMENT ON COLUMN user_groups.capabilities IS 'resource:action pairs such as posts:publish';
+COMMENT ON COLUMN user_group_members.is_manager IS 'Managers may add and remove members and notify the group';
+COMMENT ON TABLE user_group_sections IS 'Categories, with their subcategories, whose posts only owning groups may change';
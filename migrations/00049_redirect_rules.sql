@@ -0,0 +1,18 @@
+-- ============================================
+-- Migration: 00049_redirect_rules.sql
+-- Description: Redirect manager rules: regex patterns besides exact paths,
+--              temporary redirects and 410 Gone, ordering and hit tracking
+-- ============================================
+
+ALTER TABLE redirects ADD COLUMN IF NOT EXISTS match_type VARCHAR(10) NOT NULL DEFAULT 'exact';
+ALTER TABLE redirects ADD COLUMN IF NOT EXISTS position INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE redirects ADD COLUMN IF NOT EXISTS enabled BOOLEAN NOT NULL DEFAULT TRUE;
+ALTER TABLE redirects ADD COLUMN IF NOT EXISTS last_hit_at TIMESTAMP WITH TIME ZONE;
+ALTER TABLE redirects ADD COLUMN IF NOT EXISTS updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW();
+
+CREATE INDEX IF NOT EXISTS idx_redirects_match ON redirects(match_type, position);
+
+COMMENT ON COLUMN redirects.source_path IS 'Decoded path without trailing slash or query string, or a regex matched against the whole path';
+COMMENT ON COLUMN redirects.target IS 'Site path or absolute URL; regex targets may use $1 or ${name}; empty for 410 Gone';
+COMMENT ON COLUMN redirects.match_type IS 'exact or regex; exact paths are checked before patterns';
+COMMENT ON COLUMN redirects.position IS 'Order regex patterns are tried in, lowest first';
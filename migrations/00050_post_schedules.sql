@@ -0,0 +1,20 @@
+-- ============================================
+-- Migration: 00050_post_schedules.sql
+-- Description: Scheduled unpublishing (expiry), embargo windows and the
+--              timezone a post's schedule was entered in
+-- ============================================
+
+ALTER TABLE posts ADD COLUMN IF NOT EXISTS scheduled_at TIMESTAMP WITH TIME ZONE;
+ALTER TABLE posts ADD COLUMN IF NOT EXISTS expires_at TIMESTAMP WITH TIME ZONE;
+ALTER TABLE posts ADD COLUMN IF NOT EXISTS embargo_starts_at TIMESTAMP WITH TIME ZONE;
+ALTER TABLE posts ADD COLUMN IF NOT EXISTS embargo_ends_at TIMESTAMP WITH TIME ZONE;
+ALTER TABLE posts ADD COLUMN IF NOT EXISTS schedule_timezone VARCHAR(64);
+
+CREATE INDEX IF NOT EXISTS idx_posts_scheduled ON posts(scheduled_at) WHERE scheduled_at IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_posts_expires ON posts(expires_at) WHERE expires_at IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_posts_embargo ON posts(embargo_ends_at) WHERE embargo_ends_at IS NOT NULL;
+
+COMMENT ON COLUMN posts.expires_at IS 'When a published post goes back to draft';
+COMMENT ON COLUMN posts.embargo_starts_at IS 'Start of the period the post is withheld in; NULL with an end means right away';
+COMMENT ON COLUMN posts.embargo_ends_at IS 'When the embargo lifts and a withheld post is published again';
+COMMENT ON COLUMN posts.schedule_timezone IS 'IANA timezone wall-clock schedule dates were entered in';
@@ -0,0 +1,37 @@
+-- ============================================
+-- Migration: 00051_editorial_discussions.sql
+-- Description: Internal discussion threads on drafts, anchored to editor
+--              blocks and kept apart from public comments
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS discussion_threads (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    post_id UUID NOT NULL REFERENCES posts(id) ON DELETE CASCADE,
+    block_client_id VARCHAR(64),
+    quoted_text TEXT,
+    status VARCHAR(20) NOT NULL DEFAULT 'open',
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    resolved_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    resolved_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_discussion_threads_post ON discussion_threads(post_id, status, created_at);
+
+CREATE TABLE IF NOT EXISTS discussion_comments (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    thread_id UUID NOT NULL REFERENCES discussion_threads(id) ON DELETE CASCADE,
+    author_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    content TEXT NOT NULL,
+    mentions JSONB NOT NULL DEFAULT '[]',
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_discussion_comments_thread ON discussion_comments(thread_id, created_at);
+
+COMMENT ON TABLE discussion_threads IS 'Editorial discussions on drafts; never shown publicly';
+COMMENT ON COLUMN discussion_threads.block_client_id IS 'Client ID of the editor block the thread is about; NULL for the whole post';
+COMMENT ON COLUMN discussion_threads.status IS 'open or resolved';
+COMMENT ON COLUMN discussion_comments.mentions IS 'IDs of the users mentioned with @username';
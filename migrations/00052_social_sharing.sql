@@ -0,0 +1,47 @@
+-- ============================================
+-- Migration: 00052_social_sharing.sql
+-- Description: Per-post social preview settings and the queue and log of
+--              shares to social network accounts
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS social_post_settings (
+    post_id UUID PRIMARY KEY REFERENCES posts(id) ON DELETE CASCADE,
+    card_title VARCHAR(200),
+    card_subtitle VARCHAR(200),
+    auto_post BOOLEAN NOT NULL DEFAULT TRUE,
+    messages JSONB NOT NULL DEFAULT '{}',
+    updated_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS social_shares (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    post_id UUID NOT NULL REFERENCES posts(id) ON DELETE CASCADE,
+    account_id VARCHAR(64) NOT NULL,
+    network VARCHAR(20) NOT NULL,
+    message TEXT,
+    automatic BOOLEAN NOT NULL DEFAULT FALSE,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending',
+    scheduled_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    remote_id VARCHAR(255),
+    remote_url TEXT,
+    last_error TEXT,
+    log JSONB NOT NULL DEFAULT '[]',
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    sent_at TIMESTAMP WITH TIME ZONE
+);
+
+-- A post is shared automatically at most once per account
+CREATE UNIQUE INDEX IF NOT EXISTS idx_social_shares_automatic ON social_shares(post_id, account_id) WHERE automatic;
+CREATE INDEX IF NOT EXISTS idx_social_shares_due ON social_shares(scheduled_at) WHERE status = 'pending';
+CREATE INDEX IF NOT EXISTS idx_social_shares_post ON social_shares(post_id, created_at);
+
+COMMENT ON TABLE social_post_settings IS 'Preview card and message overrides of posts';
+COMMENT ON COLUMN social_post_settings.messages IS 'Message templates by social account ID';
+COMMENT ON TABLE social_shares IS 'Messages about posts to social accounts, queued, sent or failed';
+COMMENT ON COLUMN social_shares.message IS 'Text given for this share; rendered from templates when NULL';
+COMMENT ON COLUMN social_shares.status IS 'pending, sending, sent, failed or cancelled';
+COMMENT ON COLUMN social_shares.log IS 'Every send attempt with its outcome';
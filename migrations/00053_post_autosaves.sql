@@ -0,0 +1,21 @@
+-- ============================================
+-- Migration: 00053_post_autosaves.sql
+-- Description: Snapshots of posts being edited together, taken while the
+--              editing session runs and kept apart from saved revisions
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS post_autosaves (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    post_id UUID NOT NULL REFERENCES posts(id) ON DELETE CASCADE,
+    version BIGINT NOT NULL,
+    title VARCHAR(500) NOT NULL,
+    content TEXT NOT NULL,
+    contributors JSONB NOT NULL DEFAULT '[]',
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_post_autosaves_post ON post_autosaves(post_id, created_at DESC);
+
+COMMENT ON TABLE post_autosaves IS 'Autosaves of collaborative editing sessions; the newest 20 are kept per post';
+COMMENT ON COLUMN post_autosaves.version IS 'Session document version the snapshot was taken at';
+COMMENT ON COLUMN post_autosaves.contributors IS 'IDs of the users whose changes went into the snapshot';
@@ -0,0 +1,18 @@
+-- ============================================
+-- Migration: 00054_og_images.sql
+-- Description: Rasterized Open Graph images of posts, kept in storage
+--              until the preview card they were made from changes
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS og_images (
+    post_id UUID PRIMARY KEY REFERENCES posts(id) ON DELETE CASCADE,
+    svg_hash VARCHAR(64) NOT NULL,
+    path VARCHAR(500) NOT NULL,
+    content_type VARCHAR(100) NOT NULL,
+    size BIGINT NOT NULL DEFAULT 0,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON TABLE og_images IS 'Generated og:image of each post; files live under og-images/{post_id}/ in storage';
+COMMENT ON COLUMN og_images.svg_hash IS 'SHA-256 of the card SVG the image was rasterized from';
+COMMENT ON COLUMN og_images.path IS 'Storage path of the image';
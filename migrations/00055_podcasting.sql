@@ -0,0 +1,46 @@
+-- ============================================
+-- Migration: 00055_podcasting.sql
+-- Description: Podcast episodes (posts of type 'episode') with their audio
+--              enclosures, and a daily log of episode downloads
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS podcast_episodes (
+    post_id UUID PRIMARY KEY REFERENCES posts(id) ON DELETE CASCADE,
+    media_id UUID REFERENCES media(id) ON DELETE SET NULL,
+    audio_url TEXT,
+    mime_type VARCHAR(100) NOT NULL,
+    file_size BIGINT NOT NULL DEFAULT 0,
+    duration_seconds INTEGER NOT NULL,
+    season INTEGER,
+    episode_number INTEGER,
+    episode_type VARCHAR(20) NOT NULL DEFAULT 'full',
+    explicit BOOLEAN,
+    transcript_url TEXT,
+    updated_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON TABLE podcast_episodes IS 'Audio enclosure of each podcast episode';
+COMMENT ON COLUMN podcast_episodes.media_id IS 'Uploaded audio; NULL when audio_url points elsewhere';
+COMMENT ON COLUMN podcast_episodes.episode_type IS 'full, trailer or bonus, as itunes:episodeType';
+COMMENT ON COLUMN podcast_episodes.explicit IS 'Overrides the show''s explicit flag when set';
+
+CREATE TABLE IF NOT EXISTS podcast_downloads (
+    post_id UUID NOT NULL REFERENCES posts(id) ON DELETE CASCADE,
+    day DATE NOT NULL,
+    listener_hash VARCHAR(64) NOT NULL,
+    app VARCHAR(50) NOT NULL,
+    requests INTEGER NOT NULL DEFAULT 1,
+    bytes_served BIGINT NOT NULL DEFAULT 0,
+    counted BOOLEAN NOT NULL DEFAULT FALSE,
+    first_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    last_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (post_id, day, listener_hash)
+);
+
+CREATE INDEX IF NOT EXISTS idx_podcast_downloads_day ON podcast_downloads(day);
+
+COMMENT ON TABLE podcast_downloads IS 'Audio requests per episode, day and anonymous listener';
+COMMENT ON COLUMN podcast_downloads.listener_hash IS 'SHA-256 of address, user agent and day';
+COMMENT ON COLUMN podcast_downloads.counted IS 'Whether enough audio was served to count as a download';
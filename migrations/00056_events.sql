@@ -0,0 +1,37 @@
+-- ============================================
+-- Migration: 00056_events.sql
+-- Description: Event details of posts of type 'event': schedule with
+--              timezone and recurrence rule, venue, organizer and tickets
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS event_details (
+    post_id UUID PRIMARY KEY REFERENCES posts(id) ON DELETE CASCADE,
+    starts_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    ends_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    all_day BOOLEAN NOT NULL DEFAULT FALSE,
+    timezone VARCHAR(64) NOT NULL DEFAULT 'UTC',
+    rrule TEXT,
+    exdates JSONB NOT NULL DEFAULT '[]',
+    series_ends_at TIMESTAMP WITH TIME ZONE,
+    venue JSONB,
+    online_url TEXT,
+    status VARCHAR(20) NOT NULL DEFAULT 'scheduled',
+    organizer_name VARCHAR(255),
+    organizer_url TEXT,
+    ticket_url TEXT,
+    price VARCHAR(50),
+    currency CHAR(3),
+    updated_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    CONSTRAINT event_details_ends_after_start CHECK (ends_at >= starts_at)
+);
+
+CREATE INDEX IF NOT EXISTS idx_event_details_schedule ON event_details(starts_at, series_ends_at);
+
+COMMENT ON TABLE event_details IS 'Schedule, venue and tickets of each event';
+COMMENT ON COLUMN event_details.starts_at IS 'Start of the first occurrence';
+COMMENT ON COLUMN event_details.timezone IS 'IANA timezone the schedule and recurrence rule are in';
+COMMENT ON COLUMN event_details.rrule IS 'RFC 5545 recurrence rule; NULL for one-off events';
+COMMENT ON COLUMN event_details.exdates IS 'Local start times of cancelled occurrences';
+COMMENT ON COLUMN event_details.series_ends_at IS 'End of the last occurrence; NULL when the series never ends';
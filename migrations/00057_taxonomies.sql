@@ -0,0 +1,104 @@
+-- ============================================
+-- Migration: 00057_taxonomies.sql
+-- Description: Taxonomy registry with hierarchical terms, term meta and
+--              term relationships; categories and tags move into it
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS taxonomies (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    slug VARCHAR(32) NOT NULL UNIQUE,
+    name VARCHAR(100) NOT NULL,
+    singular_name VARCHAR(100),
+    description TEXT,
+    hierarchical BOOLEAN NOT NULL DEFAULT FALSE,
+    public BOOLEAN NOT NULL DEFAULT TRUE,
+    rewrite_slug VARCHAR(64) UNIQUE,
+    post_types JSONB NOT NULL DEFAULT '["post"]',
+    is_system BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON TABLE taxonomies IS 'Registered taxonomies: built-in categories and tags, and custom ones';
+COMMENT ON COLUMN taxonomies.rewrite_slug IS 'First segment of term archive URLs; NULL uses the slug';
+COMMENT ON COLUMN taxonomies.post_types IS 'Post types whose posts can carry terms of the taxonomy';
+COMMENT ON COLUMN taxonomies.is_system IS 'Built-in taxonomies cannot be deleted';
+
+CREATE TABLE IF NOT EXISTS terms (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    taxonomy_id UUID NOT NULL REFERENCES taxonomies(id) ON DELETE CASCADE,
+    parent_id UUID REFERENCES terms(id) ON DELETE SET NULL,
+    name VARCHAR(255) NOT NULL,
+    slug VARCHAR(255) NOT NULL,
+    description TEXT,
+    term_order INTEGER NOT NULL DEFAULT 0,
+    count INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    CONSTRAINT terms_taxonomy_slug_unique UNIQUE (taxonomy_id, slug)
+);
+
+CREATE INDEX IF NOT EXISTS idx_terms_parent ON terms(parent_id) WHERE parent_id IS NOT NULL;
+
+COMMENT ON TABLE terms IS 'Terms of each taxonomy; only hierarchical taxonomies have parents';
+COMMENT ON COLUMN terms.count IS 'Published posts carrying the term';
+
+CREATE TABLE IF NOT EXISTS term_meta (
+    term_id UUID NOT NULL REFERENCES terms(id) ON DELETE CASCADE,
+    meta_key VARCHAR(255) NOT NULL,
+    meta_value JSONB NOT NULL,
+    PRIMARY KEY (term_id, meta_key)
+);
+
+COMMENT ON TABLE term_meta IS 'Arbitrary JSON values attached to terms';
+
+CREATE TABLE IF NOT EXISTS term_relationships (
+    object_id UUID NOT NULL,
+    object_type VARCHAR(20) NOT NULL DEFAULT 'post',
+    term_id UUID NOT NULL REFERENCES terms(id) ON DELETE CASCADE,
+    term_order INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (object_id, object_type, term_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_term_relationships_term ON term_relationships(term_id, object_type);
+
+COMMENT ON TABLE term_relationships IS 'Terms carried by posts and other objects';
+
+-- Built-in taxonomies
+INSERT INTO taxonomies (slug, name, singular_name, hierarchical, rewrite_slug, post_types, is_system)
+VALUES
+    ('category', 'Categories', 'Category', TRUE, 'category', '["post", "episode"]', TRUE),
+    ('post_tag', 'Tags', 'Tag', FALSE, 'tag', '["post", "episode"]', TRUE)
+ON CONFLICT (slug) DO NOTHING;
+
+-- Move the legacy categories and tags over, keeping their ids
+INSERT INTO terms (id, taxonomy_id, parent_id, name, slug, description, created_at, updated_at)
+SELECT c.id, tx.id, c.parent_id, c.name, c.slug, c.description, c.created_at, c.updated_at
+FROM categories c
+JOIN taxonomies tx ON tx.slug = 'category'
+ON CONFLICT DO NOTHING;
+
+INSERT INTO terms (id, taxonomy_id, name, slug, description, created_at, updated_at)
+SELECT t.id, tx.id, t.name, t.slug, t.description, t.created_at, t.updated_at
+FROM tags t
+JOIN taxonomies tx ON tx.slug = 'post_tag'
+ON CONFLICT DO NOTHING;
+
+INSERT INTO term_relationships (object_id, object_type, term_id)
+SELECT pc.post_id, 'post', pc.category_id
+FROM post_categories pc
+JOIN terms t ON t.id = pc.category_id
+ON CONFLICT DO NOTHING;
+
+INSERT INTO term_relationships (object_id, object_type, term_id)
+SELECT pt.post_id, 'post', pt.tag_id
+FROM post_tags pt
+JOIN terms t ON t.id = pt.tag_id
+ON CONFLICT DO NOTHING;
+
+UPDATE terms t SET count = (
+    SELECT COUNT(*) FROM term_relationships tr
+    JOIN posts p ON p.id = tr.object_id
+    WHERE tr.term_id = t.id AND tr.object_type = 'post'
+      AND p.status = 'published' AND p.deleted_at IS NULL
+);
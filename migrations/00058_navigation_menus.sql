@@ -0,0 +1,39 @@
+-- ============================================
+-- Migration: 00058_navigation_menus.sql
+-- Description: Menu items link to posts, terms or custom URLs and carry
+--              visibility rules; menus are assigned to theme locations
+-- ============================================
+
+ALTER TABLE menus ADD COLUMN IF NOT EXISTS description TEXT;
+
+COMMENT ON COLUMN menus.location IS 'Location the menu fills when the active theme has no assignment for it';
+
+UPDATE menu_items SET object_type = 'custom' WHERE object_type IS NULL;
+ALTER TABLE menu_items ALTER COLUMN object_type SET DEFAULT 'custom';
+ALTER TABLE menu_items ALTER COLUMN object_type SET NOT NULL;
+
+ALTER TABLE menu_items ADD COLUMN IF NOT EXISTS visibility VARCHAR(20) NOT NULL DEFAULT 'everyone';
+ALTER TABLE menu_items ADD COLUMN IF NOT EXISTS roles JSONB NOT NULL DEFAULT '[]';
+
+CREATE INDEX IF NOT EXISTS idx_menu_items_menu_order ON menu_items(menu_id, menu_order);
+
+COMMENT ON COLUMN menu_items.object_type IS 'custom (url), post (object_id is a post of any type) or term (object_id is a term)';
+COMMENT ON COLUMN menu_items.title IS 'Empty for post and term links that follow the title of their object';
+COMMENT ON COLUMN menu_items.visibility IS 'everyone, logged_in, logged_out or roles';
+COMMENT ON COLUMN menu_items.roles IS 'Roles that see the item when visibility is roles';
+
+CREATE TABLE IF NOT EXISTS theme_menu_assignments (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    site_id UUID,
+    theme_id VARCHAR(100) NOT NULL,
+    location_slug VARCHAR(100) NOT NULL,
+    menu_id UUID NOT NULL REFERENCES menus(id) ON DELETE CASCADE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    CONSTRAINT theme_menu_assignments_location_unique
+        UNIQUE NULLS NOT DISTINCT (site_id, theme_id, location_slug)
+);
+
+CREATE INDEX IF NOT EXISTS idx_theme_menu_assignments_menu ON theme_menu_assignments(menu_id);
+
+COMMENT ON TABLE theme_menu_assignments IS 'Menu shown in each location of a theme';
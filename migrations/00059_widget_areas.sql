@@ -0,0 +1,39 @@
+-- ============================================
+-- Migration: 00059_widget_areas.sql
+-- Description: Widget areas registered from theme manifests; widgets are
+--              blocks placed into them
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS widget_areas (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    site_id UUID,
+    theme VARCHAR(100) NOT NULL,
+    slug VARCHAR(100) NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    description TEXT,
+    before_widget TEXT,
+    after_widget TEXT,
+    before_title TEXT,
+    after_title TEXT,
+    position INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    deleted_at TIMESTAMP WITH TIME ZONE,
+    CONSTRAINT widget_areas_theme_slug_unique UNIQUE NULLS NOT DISTINCT (site_id, theme, slug)
+);
+
+COMMENT ON TABLE widget_areas IS 'Widget areas each theme declares in its manifest';
+COMMENT ON COLUMN widget_areas.before_widget IS 'Markup opening each widget; {id} and {class} are replaced with the widget''s';
+COMMENT ON COLUMN widget_areas.position IS 'Order of the area in the manifest';
+COMMENT ON COLUMN widget_areas.deleted_at IS 'Set when the theme stops declaring the area; its widgets come back with it';
+
+ALTER TABLE widgets ADD COLUMN IF NOT EXISTS area_id UUID REFERENCES widget_areas(id) ON DELETE CASCADE;
+ALTER TABLE widgets ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMP WITH TIME ZONE;
+ALTER TABLE widgets ALTER COLUMN sidebar DROP NOT NULL;
+
+CREATE INDEX IF NOT EXISTS idx_widgets_area ON widgets(area_id, widget_order) WHERE deleted_at IS NULL;
+
+COMMENT ON COLUMN widgets.sidebar IS 'Area slug of widgets placed before areas were registered; the first area registered with that slug adopts them';
+COMMENT ON COLUMN widgets.widget_type IS 'Block name such as core/paragraph, or a classic widget: search, recent_posts, categories, tag_cloud, navigation, text or html';
+COMMENT ON COLUMN widgets.content IS 'Inner HTML of blocks, or their inner blocks as JSON; body of text and html widgets';
+COMMENT ON COLUMN widgets.settings IS 'Block attributes, or the options of classic widgets';
@@ -0,0 +1,37 @@
+-- ============================================
+-- Migration: 00060_sites.sql
+-- Description: Sites of a network; each has its own theme and plugin
+--              activation and can be suspended by network admins
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS sites (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    slug VARCHAR(63) NOT NULL UNIQUE,
+    name VARCHAR(255) NOT NULL,
+    domain VARCHAR(253) UNIQUE,
+    tenant VARCHAR(100),
+    status VARCHAR(20) NOT NULL DEFAULT 'active'
+        CHECK (status IN ('active', 'suspended', 'archived')),
+    status_reason TEXT,
+    owner_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    status_changed_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_sites_tenant ON sites(tenant) WHERE tenant IS NOT NULL;
+
+COMMENT ON TABLE sites IS 'Sites of the network besides the main site, whose rows have no site_id';
+COMMENT ON COLUMN sites.slug IS 'Subdomain or first path segment the site is served under';
+COMMENT ON COLUMN sites.domain IS 'Custom domain; always resolves to the site';
+COMMENT ON COLUMN sites.tenant IS 'Tenant whose extension allowlist applies; NULL uses the slug';
+COMMENT ON COLUMN sites.status IS 'active, suspended (answers 503) or archived (answers 410)';
+
+CREATE TABLE IF NOT EXISTS site_plugins (
+    site_id UUID NOT NULL REFERENCES sites(id) ON DELETE CASCADE,
+    plugin_id VARCHAR(100) NOT NULL,
+    activated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (site_id, plugin_id)
+);
+
+COMMENT ON TABLE site_plugins IS 'Plugins active on one site; network-active plugins run on every site';
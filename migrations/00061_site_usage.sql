@@ -0,0 +1,31 @@
+-- ============================================
+-- Migration: 00061_site_usage.sql
+-- Description: Plans and quotas of network sites, and the storage,
+--              bandwidth and job time each uses per month
+-- ============================================
+
+ALTER TABLE sites ADD COLUMN IF NOT EXISTS plan VARCHAR(50);
+ALTER TABLE sites ADD COLUMN IF NOT EXISTS quotas JSONB;
+
+COMMENT ON COLUMN sites.plan IS 'free, starter, professional, enterprise or a custom plan; NULL is unlimited';
+COMMENT ON COLUMN sites.quotas IS 'Quotas of the site alone, replacing those of its plan';
+
+CREATE TABLE IF NOT EXISTS site_usage (
+    site_id UUID NOT NULL REFERENCES sites(id) ON DELETE CASCADE,
+    period DATE NOT NULL,
+    storage_bytes BIGINT NOT NULL DEFAULT 0,
+    media_count BIGINT NOT NULL DEFAULT 0,
+    bandwidth_bytes BIGINT NOT NULL DEFAULT 0,
+    job_seconds BIGINT NOT NULL DEFAULT 0,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (site_id, period)
+);
+
+CREATE INDEX IF NOT EXISTS idx_site_usage_period ON site_usage(period);
+
+COMMENT ON TABLE site_usage IS 'Usage of network sites by calendar month, for billing';
+COMMENT ON COLUMN site_usage.period IS 'First day of the month';
+COMMENT ON COLUMN site_usage.storage_bytes IS 'Bytes kept at the end of the month; carried over to the next';
+COMMENT ON COLUMN site_usage.media_count IS 'Files kept at the end of the month; carried over to the next';
+COMMENT ON COLUMN site_usage.bandwidth_bytes IS 'Bytes of responses served during the month';
+COMMENT ON COLUMN site_usage.job_seconds IS 'Seconds background jobs of the site ran during the month';
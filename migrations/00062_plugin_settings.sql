@@ -0,0 +1,18 @@
+-- ============================================
+-- Migration: 00062_plugin_settings.sql
+-- Description: Saved plugin settings for the installation and each
+--              tenant, versioned for optimistic locking
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS plugin_settings (
+    plugin_id VARCHAR(100) NOT NULL,
+    tenant_id VARCHAR(100) NOT NULL DEFAULT '',
+    version BIGINT NOT NULL DEFAULT 1,
+    settings JSONB NOT NULL DEFAULT '{}',
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (plugin_id, tenant_id)
+);
+
+COMMENT ON TABLE plugin_settings IS 'Settings of each plugin, validated against its configuration schema';
+COMMENT ON COLUMN plugin_settings.tenant_id IS 'Empty for the settings of the whole installation';
+COMMENT ON COLUMN plugin_settings.version IS 'Incremented by every save';
@@ -0,0 +1,55 @@
+-- ============================================
+-- Migration: 00063_setting_change_requests.sql
+-- Description: Four-eyes approval of sensitive settings: pending change
+--              requests and the audit trail of what happened to them
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS setting_change_requests (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    status VARCHAR(20) NOT NULL DEFAULT 'pending',
+    changes JSONB NOT NULL DEFAULT '[]',
+    reason TEXT,
+    requested_by UUID NOT NULL,
+    reviewed_by UUID,
+    review_note TEXT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    decided_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX IF NOT EXISTS idx_setting_change_requests_status
+    ON setting_change_requests(status, expires_at);
+
+COMMENT ON TABLE setting_change_requests IS 'Changes to sensitive settings waiting for, or decided by, a second administrator';
+COMMENT ON COLUMN setting_change_requests.status IS 'pending, applied, rejected, cancelled, expired or superseded';
+COMMENT ON COLUMN setting_change_requests.changes IS 'Each setting with its value and version when requested and the proposed value';
+
+CREATE TABLE IF NOT EXISTS setting_change_events (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    request_id UUID NOT NULL REFERENCES setting_change_requests(id),
+    action VARCHAR(20) NOT NULL,
+    actor_id UUID,
+    note TEXT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_setting_change_events_request
+    ON setting_change_events(request_id, created_at);
+
+COMMENT ON TABLE setting_change_events IS 'Audit trail of setting change requests';
+COMMENT ON COLUMN setting_change_events.actor_id IS 'NULL when the system acted, e.g. expiring a request';
+
+-- Approval mode starts switched off
+INSERT INTO options (option_name, option_value, option_group, autoload, is_system, value_type, display_name, description)
+SELECT
+    'settings_approval',
+    '{"enabled": false, "sensitive_groups": ["security", "auth", "billing"], "sensitive_keys": [], "expiry_hours": 72}'::jsonb,
+    'security',
+    FALSE,
+    TRUE,
+    'json',
+    'Settings Approval',
+    'Require a second administrator to approve changes to sensitive settings'
+WHERE NOT EXISTS (
+    SELECT 1 FROM options WHERE option_name = 'settings_approval' AND site_id IS NULL
+);
@@ -0,0 +1,65 @@
+-- ============================================
+-- Migration: 00064_webhooks.sql
+-- Description: Outbound webhooks: registered endpoints, the deliveries of
+--              events to them and every attempt at each delivery
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS webhook_endpoints (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    owner_id UUID NOT NULL,
+    site_id UUID,
+    url TEXT NOT NULL,
+    description TEXT,
+    event_types TEXT[] NOT NULL DEFAULT '{}',
+    secret VARCHAR(255) NOT NULL,
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_webhook_endpoints_owner ON webhook_endpoints(owner_id);
+
+COMMENT ON TABLE webhook_endpoints IS 'URLs that events are posted to';
+COMMENT ON COLUMN webhook_endpoints.site_id IS 'Network site whose events are sent; NULL for every site';
+COMMENT ON COLUMN webhook_endpoints.event_types IS 'Event types or patterns (post.*, *) that are sent';
+COMMENT ON COLUMN webhook_endpoints.secret IS 'Key of the HMAC-SHA256 signature sent with every delivery';
+
+CREATE TABLE IF NOT EXISTS webhook_deliveries (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    endpoint_id UUID NOT NULL REFERENCES webhook_endpoints(id) ON DELETE CASCADE,
+    event_id UUID,
+    event_type VARCHAR(100) NOT NULL,
+    payload JSONB NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending',
+    attempts INTEGER NOT NULL DEFAULT 0,
+    response_status INTEGER,
+    last_error TEXT,
+    redelivery_of UUID REFERENCES webhook_deliveries(id) ON DELETE SET NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    delivered_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_endpoint
+    ON webhook_deliveries(endpoint_id, created_at DESC);
+
+COMMENT ON TABLE webhook_deliveries IS 'Events posted, or to be posted, to webhook endpoints';
+COMMENT ON COLUMN webhook_deliveries.status IS 'pending, succeeded or failed';
+COMMENT ON COLUMN webhook_deliveries.payload IS 'Request body, sent unchanged on every attempt and redelivery';
+COMMENT ON COLUMN webhook_deliveries.redelivery_of IS 'Delivery this one sends again, when redelivered by hand';
+
+CREATE TABLE IF NOT EXISTS webhook_delivery_attempts (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    delivery_id UUID NOT NULL REFERENCES webhook_deliveries(id) ON DELETE CASCADE,
+    attempt INTEGER NOT NULL,
+    response_status INTEGER,
+    response_body TEXT,
+    error TEXT,
+    duration_ms INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_webhook_delivery_attempts_delivery
+    ON webhook_delivery_attempts(delivery_id, attempt);
+
+COMMENT ON TABLE webhook_delivery_attempts IS 'Log of every request made for a webhook delivery';
+COMMENT ON COLUMN webhook_delivery_attempts.response_body IS 'Start of the response body';
@@ -0,0 +1,51 @@
+-- ============================================
+-- Migration: 00065_search_indexing.sql
+-- Description: Search engine indexing notifications: the URLs search
+--              engines were told about and the requests made to them
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS indexing_urls (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    post_id UUID,
+    url TEXT NOT NULL UNIQUE,
+    pending_action VARCHAR(10),
+    queued_at TIMESTAMP WITH TIME ZONE,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_action VARCHAR(10),
+    last_submitted_at TIMESTAMP WITH TIME ZONE,
+    indexnow_status VARCHAR(20),
+    indexnow_response INTEGER,
+    google_status VARCHAR(20),
+    google_response INTEGER,
+    last_error TEXT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_indexing_urls_post ON indexing_urls(post_id);
+CREATE INDEX IF NOT EXISTS idx_indexing_urls_pending
+    ON indexing_urls(queued_at) WHERE pending_action IS NOT NULL;
+
+COMMENT ON TABLE indexing_urls IS 'Site URLs and what search engines were last told about them';
+COMMENT ON COLUMN indexing_urls.pending_action IS 'updated or deleted, waiting to be submitted';
+COMMENT ON COLUMN indexing_urls.last_action IS 'Action last taken from the queue';
+COMMENT ON COLUMN indexing_urls.indexnow_status IS 'submitted or failed; NULL when IndexNow was not used';
+COMMENT ON COLUMN indexing_urls.google_status IS 'submitted or failed; NULL when the Google Indexing API was not used';
+
+CREATE TABLE IF NOT EXISTS indexing_submissions (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    engine VARCHAR(20) NOT NULL,
+    action VARCHAR(10) NOT NULL,
+    urls TEXT[] NOT NULL DEFAULT '{}',
+    response_status INTEGER,
+    response_body TEXT,
+    error TEXT,
+    duration_ms INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_indexing_submissions_created
+    ON indexing_submissions(created_at DESC);
+
+COMMENT ON TABLE indexing_submissions IS 'Log of requests made to IndexNow and the Google Indexing API';
+COMMENT ON COLUMN indexing_submissions.response_body IS 'Start of the response body';
@@ -0,0 +1,56 @@
+-- ============================================
+-- Migration: 00066_web_vitals.sql
+-- Description: Core Web Vitals field data reported by visitors' browsers,
+--              rolled up per day, and the performance budget alerts
+--              raised from them
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS web_vitals_daily (
+    day DATE NOT NULL,
+    path VARCHAR(512) NOT NULL,
+    template VARCHAR(20) NOT NULL,
+    device VARCHAR(10) NOT NULL,
+    metric VARCHAR(4) NOT NULL,
+    samples BIGINT NOT NULL DEFAULT 0,
+    good BIGINT NOT NULL DEFAULT 0,
+    needs_improvement BIGINT NOT NULL DEFAULT 0,
+    poor BIGINT NOT NULL DEFAULT 0,
+    histogram BIGINT[] NOT NULL DEFAULT '{}',
+    PRIMARY KEY (day, path, device, metric)
+);
+
+CREATE INDEX IF NOT EXISTS idx_web_vitals_daily_template
+    ON web_vitals_daily(template, day);
+
+COMMENT ON TABLE web_vitals_daily IS 'Core Web Vitals samples per day, page, device and metric';
+COMMENT ON COLUMN web_vitals_daily.template IS 'Kind of page the path renders: home, post, page, event, archive, search or other';
+COMMENT ON COLUMN web_vitals_daily.device IS 'mobile, tablet or desktop, from the User-Agent';
+COMMENT ON COLUMN web_vitals_daily.metric IS 'LCP, CLS or INP';
+COMMENT ON COLUMN web_vitals_daily.histogram IS 'Sample counts in logarithmic buckets, for percentiles';
+
+CREATE TABLE IF NOT EXISTS web_vitals_alerts (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    template VARCHAR(20) NOT NULL,
+    device VARCHAR(10),
+    metric VARCHAR(4) NOT NULL,
+    budget DOUBLE PRECISION NOT NULL,
+    p75 DOUBLE PRECISION NOT NULL,
+    baseline_p75 DOUBLE PRECISION,
+    samples BIGINT NOT NULL DEFAULT 0,
+    reason VARCHAR(20) NOT NULL,
+    opened_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    checked_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    resolved_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX IF NOT EXISTS idx_web_vitals_alerts_open
+    ON web_vitals_alerts(template, metric) WHERE resolved_at IS NULL;
+CREATE INDEX IF NOT EXISTS idx_web_vitals_alerts_opened
+    ON web_vitals_alerts(opened_at DESC);
+
+COMMENT ON TABLE web_vitals_alerts IS 'Templates whose p75 went over their performance budget';
+COMMENT ON COLUMN web_vitals_alerts.device IS 'Device the budget applies to; NULL for all devices';
+COMMENT ON COLUMN web_vitals_alerts.budget IS 'Highest p75 the budget allows';
+COMMENT ON COLUMN web_vitals_alerts.p75 IS 'p75 when last checked';
+COMMENT ON COLUMN web_vitals_alerts.baseline_p75 IS 'p75 of the window before';
+COMMENT ON COLUMN web_vitals_alerts.reason IS 'over_budget or regressed';
@@ -0,0 +1,64 @@
+-- ============================================
+-- Migration: 00067_inbound_email.sql
+-- Description: Inbound email: mailboxes whose messages become draft posts,
+--              polled over IMAP or posted by Mailgun and Amazon SES, and
+--              the log of every message received
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS inbound_mailboxes (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    site_id UUID,
+    name VARCHAR(255) NOT NULL,
+    kind VARCHAR(20) NOT NULL,
+    token VARCHAR(255) NOT NULL UNIQUE,
+    imap_host VARCHAR(255),
+    imap_port INTEGER NOT NULL DEFAULT 993,
+    imap_tls BOOLEAN NOT NULL DEFAULT TRUE,
+    imap_username VARCHAR(255),
+    imap_password TEXT,
+    imap_folder VARCHAR(255) NOT NULL DEFAULT 'INBOX',
+    signing_key TEXT,
+    ses_topic_arn VARCHAR(255),
+    allowed_senders TEXT[] NOT NULL DEFAULT '{}',
+    category_ids UUID[] NOT NULL DEFAULT '{}',
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    last_polled_at TIMESTAMP WITH TIME ZONE,
+    last_error TEXT,
+    created_by UUID NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_inbound_mailboxes_site ON inbound_mailboxes(site_id);
+
+COMMENT ON TABLE inbound_mailboxes IS 'Mailboxes whose messages are turned into draft posts';
+COMMENT ON COLUMN inbound_mailboxes.site_id IS 'Network site the posts are created on; NULL for the main site';
+COMMENT ON COLUMN inbound_mailboxes.kind IS 'imap, mailgun or ses';
+COMMENT ON COLUMN inbound_mailboxes.token IS 'Secret part of the webhook URL of Mailgun and SES mailboxes';
+COMMENT ON COLUMN inbound_mailboxes.signing_key IS 'Mailgun webhook signing key';
+COMMENT ON COLUMN inbound_mailboxes.ses_topic_arn IS 'SNS topic SES notifications must come from';
+COMMENT ON COLUMN inbound_mailboxes.allowed_senders IS 'Addresses or @domains that may post; empty for any user who can create posts';
+COMMENT ON COLUMN inbound_mailboxes.category_ids IS 'Categories of the posts created';
+
+CREATE TABLE IF NOT EXISTS inbound_messages (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    mailbox_id UUID NOT NULL REFERENCES inbound_mailboxes(id) ON DELETE CASCADE,
+    message_id VARCHAR(500) NOT NULL,
+    sender VARCHAR(255),
+    subject TEXT,
+    status VARCHAR(20) NOT NULL DEFAULT 'processing',
+    post_id UUID,
+    author_id UUID,
+    attachments INTEGER NOT NULL DEFAULT 0,
+    error TEXT,
+    received_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    UNIQUE (mailbox_id, message_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_inbound_messages_mailbox
+    ON inbound_messages(mailbox_id, received_at DESC);
+
+COMMENT ON TABLE inbound_messages IS 'Messages received by inbound mailboxes';
+COMMENT ON COLUMN inbound_messages.message_id IS 'Message-ID header, or a hash of the message without one; received once';
+COMMENT ON COLUMN inbound_messages.status IS 'processing, created, rejected or failed';
+COMMENT ON COLUMN inbound_messages.post_id IS 'Draft post created from the message';
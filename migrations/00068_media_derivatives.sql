@@ -0,0 +1,21 @@
+-- ============================================
+-- Migration: 00068_media_derivatives.sql
+-- Description: Resized versions of media library images, generated on
+--              first request and offered to browsers through srcset
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS media_derivatives (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    media_id UUID NOT NULL REFERENCES media(id) ON DELETE CASCADE,
+    size_name VARCHAR(100) NOT NULL,
+    storage_path TEXT NOT NULL,
+    width INTEGER NOT NULL,
+    height INTEGER NOT NULL,
+    file_size BIGINT NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    UNIQUE (media_id, size_name)
+);
+
+COMMENT ON TABLE media_derivatives IS 'Resized versions of media library images';
+COMMENT ON COLUMN media_derivatives.size_name IS 'Registered image size the version was made for';
+COMMENT ON COLUMN media_derivatives.storage_path IS 'Path of the WebP file in storage';
@@ -0,0 +1,28 @@
+-- ============================================
+-- Migration: 00069_device_authorizations.sql
+-- Description: OAuth2 device authorization grants for CLI login, shared
+--              by every instance so a poll may reach any of them
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS oauth_device_authorizations (
+    id UUID PRIMARY KEY,
+    device_code_hash VARCHAR(64) NOT NULL UNIQUE,
+    user_code VARCHAR(16) NOT NULL,
+    client_id VARCHAR(100) NOT NULL,
+    scopes JSONB NOT NULL DEFAULT '[]',
+    status VARCHAR(20) NOT NULL DEFAULT 'pending',
+    user_id UUID REFERENCES users(id) ON DELETE CASCADE,
+    interval_secs BIGINT NOT NULL,
+    last_polled_at TIMESTAMP WITH TIME ZONE,
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_oauth_device_authorizations_user_code
+    ON oauth_device_authorizations(user_code);
+CREATE INDEX IF NOT EXISTS idx_oauth_device_authorizations_expires
+    ON oauth_device_authorizations(expires_at);
+
+COMMENT ON TABLE oauth_device_authorizations IS 'OAuth2 device authorization grants (RFC 8628)';
+COMMENT ON COLUMN oauth_device_authorizations.device_code_hash IS 'SHA-256 of the device code the client polls with';
+COMMENT ON COLUMN oauth_device_authorizations.status IS 'pending, approved, denied or consumed';
@@ -0,0 +1,34 @@
+-- ============================================
+-- Migration: 00070_site_members.sql
+-- Description: Users who may act on a site of the network, and site_id
+--              on posts, media, menus and search documents so each site
+--              keeps its own
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS site_users (
+    site_id UUID NOT NULL REFERENCES sites(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    added_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (site_id, user_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_site_users_user ON site_users(user_id);
+
+COMMENT ON TABLE site_users IS 'Users who may act on a site besides its owner; network admins act on every site';
+
+ALTER TABLE posts ADD COLUMN IF NOT EXISTS site_id UUID;
+ALTER TABLE media ADD COLUMN IF NOT EXISTS site_id UUID;
+ALTER TABLE menus ADD COLUMN IF NOT EXISTS site_id UUID;
+
+CREATE INDEX IF NOT EXISTS idx_posts_site ON posts(site_id);
+CREATE INDEX IF NOT EXISTS idx_media_site ON media(site_id);
+
+-- Sites pick menu slugs of their own
+ALTER TABLE menus DROP CONSTRAINT IF EXISTS menus_slug_key;
+ALTER TABLE menus ADD CONSTRAINT menus_site_slug_unique UNIQUE NULLS NOT DISTINCT (site_id, slug);
+
+COMMENT ON COLUMN menus.site_id IS 'Site the menu belongs to; NULL for the main site';
+
+-- Searches only match documents of their own site
+ALTER TABLE search_documents ADD COLUMN IF NOT EXISTS site_id UUID;
+CREATE INDEX IF NOT EXISTS idx_search_documents_site ON search_documents(site_id);
@@ -0,0 +1,9 @@
+-- ============================================
+-- Migration: 00071_webhook_http_signatures.sql
+-- Description: Webhook endpoints that also want deliveries signed with the
+--              site's outbound HTTP message signature key
+-- ============================================
+
+ALTER TABLE webhook_endpoints ADD COLUMN IF NOT EXISTS http_signature BOOLEAN NOT NULL DEFAULT FALSE;
+
+COMMENT ON COLUMN webhook_endpoints.http_signature IS 'Whether deliveries carry an RFC 9421 signature by the newest outbound key';
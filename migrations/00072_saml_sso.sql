@@ -0,0 +1,25 @@
+-- ============================================
+-- Migration: 00072_saml_sso.sql
+-- Description: Pending SAML login requests and consumed assertions, shared
+--              by every instance so the IdP may post back to any of them
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS saml_requests (
+    id VARCHAR(64) PRIMARY KEY,
+    provider VARCHAR(100) NOT NULL,
+    redirect_after TEXT,
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_saml_requests_expires ON saml_requests(expires_at);
+
+CREATE TABLE IF NOT EXISTS saml_assertions (
+    id VARCHAR(255) PRIMARY KEY,
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_saml_assertions_expires ON saml_assertions(expires_at);
+
+COMMENT ON TABLE saml_requests IS 'SAML AuthnRequests awaiting a response from the identity provider';
+COMMENT ON TABLE saml_assertions IS 'IDs of accepted SAML assertions, kept until they expire so none is replayed';
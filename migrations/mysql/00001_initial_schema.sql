@@ -0,0 +1,239 @@
+-- RustPress Initial Schema (MySQL / MariaDB)
+-- Creates the base tables for the CMS
+--
+-- Dialect notes, shared by every migration in this directory:
+--   UUID                      -> CHAR(36) DEFAULT (UUID())
+--   JSONB                     -> JSON
+--   TIMESTAMP WITH TIME ZONE  -> DATETIME(6), always UTC (the provider pins
+--                                the session time_zone to +00:00)
+--   ON CONFLICT DO NOTHING    -> INSERT IGNORE
+-- Requires MySQL 8.0.16+ or MariaDB 10.4+ for expression defaults and
+-- enforced CHECK constraints.
+
+-- Users table
+CREATE TABLE IF NOT EXISTS users (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    email VARCHAR(255) NOT NULL UNIQUE,
+    username VARCHAR(100) NOT NULL UNIQUE,
+    password_hash VARCHAR(255) NOT NULL,
+    display_name VARCHAR(255),
+    avatar_url VARCHAR(500),
+    role VARCHAR(50) NOT NULL DEFAULT 'subscriber',
+    status VARCHAR(50) NOT NULL DEFAULT 'active',
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    last_login_at DATETIME(6),
+    email_verified_at DATETIME(6),
+    meta JSON DEFAULT (JSON_OBJECT())
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+-- Posts table
+CREATE TABLE IF NOT EXISTS posts (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    title VARCHAR(500) NOT NULL,
+    slug VARCHAR(500) NOT NULL UNIQUE,
+    content LONGTEXT,
+    excerpt TEXT,
+    status VARCHAR(50) NOT NULL DEFAULT 'draft',
+    post_type VARCHAR(50) NOT NULL DEFAULT 'post',
+    author_id CHAR(36),
+    featured_image_id CHAR(36),
+    published_at DATETIME(6),
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    meta JSON DEFAULT (JSON_OBJECT()),
+    INDEX idx_posts_status (status),
+    INDEX idx_posts_author (author_id),
+    INDEX idx_posts_published_at (published_at),
+    INDEX idx_posts_slug (slug),
+    CONSTRAINT fk_posts_author FOREIGN KEY (author_id) REFERENCES users(id) ON DELETE SET NULL
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+-- Pages table (similar to posts but for static pages)
+CREATE TABLE IF NOT EXISTS pages (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    title VARCHAR(500) NOT NULL,
+    slug VARCHAR(500) NOT NULL UNIQUE,
+    content LONGTEXT,
+    status VARCHAR(50) NOT NULL DEFAULT 'draft',
+    author_id CHAR(36),
+    parent_id CHAR(36),
+    template VARCHAR(100),
+    menu_order INTEGER NOT NULL DEFAULT 0,
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    meta JSON DEFAULT (JSON_OBJECT()),
+    INDEX idx_pages_slug (slug),
+    CONSTRAINT fk_pages_author FOREIGN KEY (author_id) REFERENCES users(id) ON DELETE SET NULL,
+    CONSTRAINT fk_pages_parent FOREIGN KEY (parent_id) REFERENCES pages(id) ON DELETE SET NULL
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+-- Categories table
+CREATE TABLE IF NOT EXISTS categories (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    name VARCHAR(255) NOT NULL,
+    slug VARCHAR(255) NOT NULL UNIQUE,
+    description TEXT,
+    parent_id CHAR(36),
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    CONSTRAINT fk_categories_parent FOREIGN KEY (parent_id) REFERENCES categories(id) ON DELETE SET NULL
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+-- Tags table
+CREATE TABLE IF NOT EXISTS tags (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    name VARCHAR(255) NOT NULL,
+    slug VARCHAR(255) NOT NULL UNIQUE,
+    description TEXT,
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+-- Post categories junction table
+CREATE TABLE IF NOT EXISTS post_categories (
+    post_id CHAR(36) NOT NULL,
+    category_id CHAR(36) NOT NULL,
+    PRIMARY KEY (post_id, category_id),
+    CONSTRAINT fk_post_categories_post FOREIGN KEY (post_id) REFERENCES posts(id) ON DELETE CASCADE,
+    CONSTRAINT fk_post_categories_category FOREIGN KEY (category_id) REFERENCES categories(id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+-- Post tags junction table
+CREATE TABLE IF NOT EXISTS post_tags (
+    post_id CHAR(36) NOT NULL,
+    tag_id CHAR(36) NOT NULL,
+    PRIMARY KEY (post_id, tag_id),
+    CONSTRAINT fk_post_tags_post FOREIGN KEY (post_id) REFERENCES posts(id) ON DELETE CASCADE,
+    CONSTRAINT fk_post_tags_tag FOREIGN KEY (tag_id) REFERENCES tags(id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+-- Media table
+CREATE TABLE IF NOT EXISTS media (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    filename VARCHAR(500) NOT NULL,
+    original_filename VARCHAR(500) NOT NULL,
+    mime_type VARCHAR(100) NOT NULL,
+    file_size BIGINT NOT NULL,
+    width INTEGER,
+    height INTEGER,
+    alt_text VARCHAR(500),
+    caption TEXT,
+    uploaded_by CHAR(36),
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    meta JSON DEFAULT (JSON_OBJECT()),
+    INDEX idx_media_uploaded_by (uploaded_by),
+    CONSTRAINT fk_media_uploaded_by FOREIGN KEY (uploaded_by) REFERENCES users(id) ON DELETE SET NULL
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+-- Comments table
+CREATE TABLE IF NOT EXISTS comments (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    post_id CHAR(36) NOT NULL,
+    parent_id CHAR(36),
+    author_id CHAR(36),
+    author_name VARCHAR(255),
+    author_email VARCHAR(255),
+    author_url VARCHAR(500),
+    content TEXT NOT NULL,
+    status VARCHAR(50) NOT NULL DEFAULT 'pending',
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    INDEX idx_comments_post (post_id),
+    INDEX idx_comments_status (status),
+    CONSTRAINT fk_comments_post FOREIGN KEY (post_id) REFERENCES posts(id) ON DELETE CASCADE,
+    CONSTRAINT fk_comments_parent FOREIGN KEY (parent_id) REFERENCES comments(id) ON DELETE CASCADE,
+    CONSTRAINT fk_comments_author FOREIGN KEY (author_id) REFERENCES users(id) ON DELETE SET NULL
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+-- Settings table (`key` is a reserved word in MySQL)
+CREATE TABLE IF NOT EXISTS settings (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    `key` VARCHAR(255) NOT NULL UNIQUE,
+    value TEXT,
+    type VARCHAR(50) NOT NULL DEFAULT 'string',
+    group_name VARCHAR(100) NOT NULL DEFAULT 'general',
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    INDEX idx_settings_key (`key`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+-- Themes table
+CREATE TABLE IF NOT EXISTS themes (
+    id VARCHAR(100) PRIMARY KEY,
+    name VARCHAR(255) NOT NULL,
+    version VARCHAR(50) NOT NULL,
+    description TEXT,
+    author VARCHAR(255),
+    author_url VARCHAR(500),
+    screenshot_url VARCHAR(500),
+    is_active BOOLEAN NOT NULL DEFAULT FALSE,
+    settings JSON DEFAULT (JSON_OBJECT()),
+    installed_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+-- Menus table
+CREATE TABLE IF NOT EXISTS menus (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    name VARCHAR(255) NOT NULL,
+    slug VARCHAR(255) NOT NULL UNIQUE,
+    location VARCHAR(100),
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+-- Menu items table
+CREATE TABLE IF NOT EXISTS menu_items (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    menu_id CHAR(36) NOT NULL,
+    parent_id CHAR(36),
+    title VARCHAR(255) NOT NULL,
+    url VARCHAR(500),
+    target VARCHAR(50) DEFAULT '_self',
+    object_type VARCHAR(50),
+    object_id CHAR(36),
+    menu_order INTEGER NOT NULL DEFAULT 0,
+    css_classes VARCHAR(500),
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    INDEX idx_menu_items_menu (menu_id),
+    CONSTRAINT fk_menu_items_menu FOREIGN KEY (menu_id) REFERENCES menus(id) ON DELETE CASCADE,
+    CONSTRAINT fk_menu_items_parent FOREIGN KEY (parent_id) REFERENCES menu_items(id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+-- Widgets table
+CREATE TABLE IF NOT EXISTS widgets (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    sidebar VARCHAR(100) NOT NULL,
+    widget_type VARCHAR(100) NOT NULL,
+    title VARCHAR(255),
+    content TEXT,
+    settings JSON DEFAULT (JSON_OBJECT()),
+    widget_order INTEGER NOT NULL DEFAULT 0,
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+-- Insert default settings
+INSERT IGNORE INTO settings (`key`, value, type, group_name) VALUES
+    ('site_title', 'RustPress', 'string', 'general'),
+    ('site_tagline', 'A Modern CMS Built with Rust', 'string', 'general'),
+    ('site_url', 'http://localhost:8080', 'string', 'general'),
+    ('admin_email', 'admin@rustpress.local', 'string', 'general'),
+    ('posts_per_page', '10', 'integer', 'reading'),
+    ('date_format', 'F j, Y', 'string', 'general'),
+    ('time_format', 'g:i a', 'string', 'general'),
+    ('timezone', 'UTC', 'string', 'general'),
+    ('comments_enabled', 'true', 'boolean', 'discussion'),
+    ('comment_moderation', 'true', 'boolean', 'discussion');
+
+-- Insert default admin user (password: admin123)
+INSERT IGNORE INTO users (email, username, password_hash, display_name, role, status, email_verified_at) VALUES
+    ('admin@rustpress.local', 'admin', '$argon2id$v=19$m=19456,t=2,p=1$ciLQC8dPihNq8CTLEnStlw$ilGVcVTxhIy20fr0wZtLwSfTsTtKny4DGmp0BfmMUYo', 'Administrator', 'administrator', 'active', CURRENT_TIMESTAMP(6));
+
+-- Insert default category
+INSERT IGNORE INTO categories (name, slug, description) VALUES
+    ('Uncategorized', 'uncategorized', 'Default category for posts');
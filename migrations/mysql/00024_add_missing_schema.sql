@@ -0,0 +1,102 @@
+-- Add missing columns and tables to match code expectations
+
+-- Add missing columns to users table
+ALTER TABLE users
+    ADD COLUMN locale VARCHAR(50),
+    ADD COLUMN timezone VARCHAR(100),
+    ADD COLUMN deleted_at DATETIME(6);
+
+-- Create sessions table for user session management
+CREATE TABLE IF NOT EXISTS sessions (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    user_id CHAR(36) NOT NULL,
+    token_hash VARCHAR(255) NOT NULL,
+    user_agent TEXT,
+    ip_address VARCHAR(45),
+    last_activity_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    expires_at DATETIME(6) NOT NULL,
+    revoked_at DATETIME(6),
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    INDEX idx_sessions_user_id (user_id),
+    INDEX idx_sessions_token_hash (token_hash),
+    CONSTRAINT fk_sessions_user FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+-- Create options table (distinct from settings, used by OptionsRepository)
+CREATE TABLE IF NOT EXISTS options (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    site_id CHAR(36),
+    option_name VARCHAR(255) NOT NULL,
+    option_value JSON,
+    option_group VARCHAR(100) NOT NULL DEFAULT 'general',
+    autoload BOOLEAN NOT NULL DEFAULT TRUE,
+    is_system BOOLEAN NOT NULL DEFAULT FALSE,
+    value_type VARCHAR(50),
+    validation JSON,
+    display_name VARCHAR(255),
+    description TEXT,
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    UNIQUE KEY uq_options_site_name (site_id, option_name),
+    INDEX idx_options_site_id (site_id),
+    INDEX idx_options_option_name (option_name),
+    INDEX idx_options_option_group (option_group),
+    INDEX idx_options_autoload (autoload)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+-- Migrate existing settings to options table. Values are written as JSON
+-- text, which both MySQL's JSON type and MariaDB's JSON alias accept.
+INSERT IGNORE INTO options (option_name, option_value, option_group, autoload, is_system)
+SELECT
+    `key` AS option_name,
+    CASE
+        WHEN type = 'boolean' THEN IF(value = 'true', 'true', 'false')
+        WHEN type = 'integer' THEN CAST(CAST(value AS SIGNED) AS CHAR)
+        ELSE JSON_QUOTE(value)
+    END AS option_value,
+    group_name AS option_group,
+    TRUE AS autoload,
+    TRUE AS is_system
+FROM settings;
+
+-- Create media_folders table
+CREATE TABLE IF NOT EXISTS media_folders (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    name VARCHAR(255) NOT NULL,
+    parent_id CHAR(36),
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    INDEX idx_media_folders_parent_id (parent_id),
+    CONSTRAINT fk_media_folders_parent FOREIGN KEY (parent_id) REFERENCES media_folders(id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+-- Add missing columns to media table, with the folder foreign key
+ALTER TABLE media
+    ADD COLUMN folder_id CHAR(36),
+    ADD COLUMN title VARCHAR(500),
+    ADD COLUMN description TEXT,
+    ADD COLUMN deleted_at DATETIME(6),
+    ADD CONSTRAINT fk_media_folder
+        FOREIGN KEY (folder_id) REFERENCES media_folders(id) ON DELETE SET NULL;
+
+-- Add missing columns to comments table
+ALTER TABLE comments
+    ADD COLUMN likes_count INTEGER DEFAULT 0,
+    ADD COLUMN deleted_at DATETIME(6);
+
+-- Create comment_likes table. MariaDB cannot index COALESCE() directly, so
+-- the null-safe unique key goes through virtual generated columns (stored
+-- ones would forbid the cascading foreign key on user_id).
+CREATE TABLE IF NOT EXISTS comment_likes (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    comment_id CHAR(36) NOT NULL,
+    user_id CHAR(36),
+    ip_address VARCHAR(45),
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    user_key CHAR(36) AS (COALESCE(user_id, '00000000-0000-0000-0000-000000000000')) VIRTUAL,
+    ip_key VARCHAR(45) AS (COALESCE(ip_address, '')) VIRTUAL,
+    UNIQUE KEY idx_comment_likes_unique (comment_id, user_key, ip_key),
+    INDEX idx_comment_likes_comment_id (comment_id),
+    CONSTRAINT fk_comment_likes_comment FOREIGN KEY (comment_id) REFERENCES comments(id) ON DELETE CASCADE,
+    CONSTRAINT fk_comment_likes_user FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
@@ -0,0 +1,165 @@
+-- ============================================
+-- Migration: 00025_collaboration_and_chat.sql (MySQL / MariaDB)
+-- Description: Real-time collaboration and chat system
+-- ============================================
+--
+-- The PostgreSQL version references sites(id), which none of these
+-- migrations create, so site_id is left without a foreign key here.
+-- Partial indexes become plain indexes, and the updated_at triggers become
+-- ON UPDATE CURRENT_TIMESTAMP columns.
+
+-- ============================================
+-- Collaboration: User Presence & Sessions
+-- ============================================
+
+-- Tracks active WebSocket sessions for collaboration
+CREATE TABLE collaboration_sessions (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    user_id CHAR(36) NOT NULL,
+    session_token VARCHAR(255) NOT NULL UNIQUE,
+    connected_at DATETIME(6) DEFAULT CURRENT_TIMESTAMP(6),
+    last_heartbeat DATETIME(6) DEFAULT CURRENT_TIMESTAMP(6),
+    status VARCHAR(50) NOT NULL DEFAULT 'online',
+    metadata JSON DEFAULT (JSON_OBJECT()),
+    created_at DATETIME(6) DEFAULT CURRENT_TIMESTAMP(6),
+    INDEX idx_collaboration_sessions_user (user_id),
+    INDEX idx_collaboration_sessions_status (status),
+    INDEX idx_collaboration_sessions_heartbeat (last_heartbeat),
+    CONSTRAINT fk_collaboration_sessions_user FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+-- Tracks which files each user has open and their cursor position.
+-- InnoDB keys are limited to 3072 bytes, so file_path is indexed by prefix.
+CREATE TABLE file_presence (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    session_id CHAR(36) NOT NULL,
+    user_id CHAR(36) NOT NULL,
+    file_path VARCHAR(1024) NOT NULL,
+    cursor_line INTEGER,
+    cursor_column INTEGER,
+    selection_start_line INTEGER,
+    selection_start_column INTEGER,
+    selection_end_line INTEGER,
+    selection_end_column INTEGER,
+    user_color VARCHAR(7) NOT NULL,
+    updated_at DATETIME(6) DEFAULT CURRENT_TIMESTAMP(6) ON UPDATE CURRENT_TIMESTAMP(6),
+    UNIQUE KEY uq_file_presence_session_file (session_id, file_path(700)),
+    INDEX idx_file_presence_file (file_path(700)),
+    INDEX idx_file_presence_session (session_id),
+    INDEX idx_file_presence_user (user_id),
+    CONSTRAINT fk_file_presence_session FOREIGN KEY (session_id) REFERENCES collaboration_sessions(id) ON DELETE CASCADE,
+    CONSTRAINT fk_file_presence_user FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+-- ============================================
+-- Chat System: Conversations
+-- ============================================
+
+-- Chat conversations (direct messages, groups, channels)
+CREATE TABLE chat_conversations (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    site_id CHAR(36),
+    title VARCHAR(255),
+    type VARCHAR(50) NOT NULL DEFAULT 'direct',
+    created_by CHAR(36) NOT NULL,
+    is_archived BOOLEAN DEFAULT FALSE,
+    metadata JSON DEFAULT (JSON_OBJECT()),
+    created_at DATETIME(6) DEFAULT CURRENT_TIMESTAMP(6),
+    updated_at DATETIME(6) DEFAULT CURRENT_TIMESTAMP(6) ON UPDATE CURRENT_TIMESTAMP(6),
+    INDEX idx_chat_conversations_site (site_id),
+    INDEX idx_chat_conversations_created_by (created_by),
+    INDEX idx_chat_conversations_type (type),
+    CONSTRAINT fk_chat_conversations_created_by FOREIGN KEY (created_by) REFERENCES users(id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+-- Participants in each conversation
+CREATE TABLE chat_conversation_participants (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    conversation_id CHAR(36) NOT NULL,
+    user_id CHAR(36) NOT NULL,
+    role VARCHAR(50) DEFAULT 'member',
+    joined_at DATETIME(6) DEFAULT CURRENT_TIMESTAMP(6),
+    last_read_at DATETIME(6),
+    is_muted BOOLEAN DEFAULT FALSE,
+    UNIQUE KEY uq_chat_participants (conversation_id, user_id),
+    INDEX idx_chat_conversation_participants_user (user_id),
+    INDEX idx_chat_conversation_participants_conversation (conversation_id),
+    CONSTRAINT fk_chat_participants_conversation FOREIGN KEY (conversation_id) REFERENCES chat_conversations(id) ON DELETE CASCADE,
+    CONSTRAINT fk_chat_participants_user FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+-- Tags for organizing conversations
+CREATE TABLE chat_conversation_tags (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    conversation_id CHAR(36) NOT NULL,
+    tag VARCHAR(100) NOT NULL,
+    color VARCHAR(7),
+    created_at DATETIME(6) DEFAULT CURRENT_TIMESTAMP(6),
+    UNIQUE KEY uq_chat_conversation_tags (conversation_id, tag),
+    CONSTRAINT fk_chat_tags_conversation FOREIGN KEY (conversation_id) REFERENCES chat_conversations(id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+-- ============================================
+-- Chat System: Messages
+-- ============================================
+
+-- Chat messages
+CREATE TABLE chat_messages (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    conversation_id CHAR(36) NOT NULL,
+    sender_id CHAR(36) NOT NULL,
+    content TEXT NOT NULL,
+    content_type VARCHAR(50) DEFAULT 'text',
+    reply_to_id CHAR(36),
+    is_pinned BOOLEAN DEFAULT FALSE,
+    is_edited BOOLEAN DEFAULT FALSE,
+    edited_at DATETIME(6),
+    metadata JSON DEFAULT (JSON_OBJECT()),
+    created_at DATETIME(6) DEFAULT CURRENT_TIMESTAMP(6),
+    deleted_at DATETIME(6),
+    INDEX idx_chat_messages_conversation (conversation_id, created_at),
+    INDEX idx_chat_messages_sender (sender_id),
+    INDEX idx_chat_messages_pinned (conversation_id, is_pinned),
+    CONSTRAINT fk_chat_messages_conversation FOREIGN KEY (conversation_id) REFERENCES chat_conversations(id) ON DELETE CASCADE,
+    CONSTRAINT fk_chat_messages_sender FOREIGN KEY (sender_id) REFERENCES users(id) ON DELETE CASCADE,
+    CONSTRAINT fk_chat_messages_reply_to FOREIGN KEY (reply_to_id) REFERENCES chat_messages(id) ON DELETE SET NULL
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+-- Reactions on messages (emoji)
+CREATE TABLE chat_message_reactions (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    message_id CHAR(36) NOT NULL,
+    user_id CHAR(36) NOT NULL,
+    emoji VARCHAR(50) NOT NULL,
+    created_at DATETIME(6) DEFAULT CURRENT_TIMESTAMP(6),
+    UNIQUE KEY uq_chat_message_reactions (message_id, user_id, emoji),
+    INDEX idx_chat_message_reactions_message (message_id),
+    CONSTRAINT fk_chat_reactions_message FOREIGN KEY (message_id) REFERENCES chat_messages(id) ON DELETE CASCADE,
+    CONSTRAINT fk_chat_reactions_user FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+-- Starred/bookmarked messages
+CREATE TABLE chat_message_stars (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    message_id CHAR(36) NOT NULL,
+    user_id CHAR(36) NOT NULL,
+    created_at DATETIME(6) DEFAULT CURRENT_TIMESTAMP(6),
+    UNIQUE KEY uq_chat_message_stars (message_id, user_id),
+    INDEX idx_chat_message_stars_user (user_id),
+    CONSTRAINT fk_chat_stars_message FOREIGN KEY (message_id) REFERENCES chat_messages(id) ON DELETE CASCADE,
+    CONSTRAINT fk_chat_stars_user FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+-- Message reminders
+CREATE TABLE chat_message_reminders (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    message_id CHAR(36) NOT NULL,
+    user_id CHAR(36) NOT NULL,
+    remind_at DATETIME(6) NOT NULL,
+    is_sent BOOLEAN DEFAULT FALSE,
+    created_at DATETIME(6) DEFAULT CURRENT_TIMESTAMP(6),
+    INDEX idx_chat_reminders_pending (is_sent, remind_at),
+    INDEX idx_chat_reminders_user (user_id),
+    CONSTRAINT fk_chat_reminders_message FOREIGN KEY (message_id) REFERENCES chat_messages(id) ON DELETE CASCADE,
+    CONSTRAINT fk_chat_reminders_user FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
@@ -0,0 +1,102 @@
+-- Storage configuration tables for managing storage backends (MySQL / MariaDB)
+-- Each content type (themes, assets, functions, plugins, apps) can have its own storage configuration
+--
+-- As in 00025, site_id has no foreign key because sites is not created by
+-- these migrations, and partial indexes become plain indexes.
+
+-- Storage configurations table
+CREATE TABLE IF NOT EXISTS storage_configurations (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    site_id CHAR(36),
+    category VARCHAR(50) NOT NULL CHECK (category IN ('themes', 'assets', 'functions', 'plugins', 'apps')),
+    provider VARCHAR(50) NOT NULL DEFAULT 'local',
+    config JSON NOT NULL DEFAULT (JSON_OBJECT()),
+    is_active BOOLEAN NOT NULL DEFAULT true,
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    deleted_at DATETIME(6),
+    UNIQUE KEY uq_storage_configurations_site_category (site_id, category),
+    INDEX idx_storage_configurations_site (site_id, deleted_at),
+    INDEX idx_storage_configurations_category (category, deleted_at)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='Storage backend configurations for different content types (themes, assets, functions, plugins, apps)';
+
+-- Storage migrations table for tracking media transfer jobs
+CREATE TABLE IF NOT EXISTS storage_migrations (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    site_id CHAR(36),
+    source_category VARCHAR(50) NOT NULL,
+    target_provider VARCHAR(50) NOT NULL,
+    target_config JSON NOT NULL DEFAULT (JSON_OBJECT()),
+    asset_types JSON NOT NULL DEFAULT (JSON_ARRAY('all')),
+    update_references BOOLEAN NOT NULL DEFAULT true,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'in_progress', 'completed', 'failed', 'cancelled')),
+    total_files BIGINT NOT NULL DEFAULT 0,
+    migrated_files BIGINT NOT NULL DEFAULT 0,
+    failed_files BIGINT NOT NULL DEFAULT 0,
+    current_file TEXT,
+    error TEXT,
+    started_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    completed_at DATETIME(6),
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    -- Columns for resuming interrupted migrations
+    last_processed_file_id CHAR(36),
+    can_resume BOOLEAN NOT NULL DEFAULT true,
+    batch_size INT NOT NULL DEFAULT 10,
+    INDEX idx_storage_migrations_site (site_id),
+    INDEX idx_storage_migrations_status (status)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='Track media migration jobs between storage backends';
+
+-- Insert default local configurations for each category
+INSERT IGNORE INTO storage_configurations (category, provider, config, is_active)
+VALUES
+    ('themes', 'local', '{"local_path": "/var/rustpress/themes"}', true),
+    ('assets', 'local', '{"local_path": "/var/rustpress/assets"}', true),
+    ('functions', 'local', '{"local_path": "/var/rustpress/functions"}', true),
+    ('plugins', 'local', '{"local_path": "/var/rustpress/plugins"}', true),
+    ('apps', 'local', '{"local_path": "/var/rustpress/apps"}', true);
+
+-- Add storage columns to media table
+ALTER TABLE media
+    ADD COLUMN storage_backend VARCHAR(50) DEFAULT 'local',
+    ADD COLUMN storage_path TEXT,
+    ADD COLUMN cdn_url TEXT;
+
+-- Migration file tracking for checkpointing and recovery
+CREATE TABLE IF NOT EXISTS storage_migration_files (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    migration_id CHAR(36) NOT NULL,
+    media_id CHAR(36),
+    source_path TEXT NOT NULL,
+    target_path TEXT,
+    file_size BIGINT NOT NULL DEFAULT 0,
+    checksum TEXT,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'transferring', 'verifying', 'completed', 'failed', 'skipped')),
+    bytes_transferred BIGINT NOT NULL DEFAULT 0,
+    attempt_count INT NOT NULL DEFAULT 0,
+    last_error TEXT,
+    started_at DATETIME(6),
+    completed_at DATETIME(6),
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    -- Indexes for efficient migration checkpoint queries; the composite
+    -- index also serves the pending-files lookup
+    INDEX idx_migration_files_migration (migration_id),
+    INDEX idx_migration_files_status (migration_id, status),
+    CONSTRAINT fk_migration_files_migration FOREIGN KEY (migration_id) REFERENCES storage_migrations(id) ON DELETE CASCADE,
+    CONSTRAINT fk_migration_files_media FOREIGN KEY (media_id) REFERENCES media(id) ON DELETE SET NULL
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='Track individual file transfers during migration for checkpointing and recovery';
+
+-- Migration checkpoints for resumable transfers
+CREATE TABLE IF NOT EXISTS storage_migration_checkpoints (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    migration_id CHAR(36) NOT NULL,
+    checkpoint_type VARCHAR(20) NOT NULL CHECK (checkpoint_type IN ('progress', 'position', 'state')),
+    data JSON NOT NULL DEFAULT (JSON_OBJECT()),
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    -- Only keep latest checkpoint per migration/type
+    UNIQUE KEY idx_migration_checkpoint_unique (migration_id, checkpoint_type),
+    CONSTRAINT fk_migration_checkpoints_migration FOREIGN KEY (migration_id) REFERENCES storage_migrations(id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='Store checkpoints for resumable migrations';
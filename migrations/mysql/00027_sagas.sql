@@ -0,0 +1,20 @@
+-- ============================================
+-- Migration: 00027_sagas.sql (MySQL / MariaDB)
+-- Description: Persisted saga state for multi-step operations
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS sagas (
+    id CHAR(36) PRIMARY KEY,
+    saga_type VARCHAR(255) NOT NULL,
+    tenant_id CHAR(36),
+    status VARCHAR(20) NOT NULL DEFAULT 'running' CHECK (status IN ('running', 'completed', 'compensating', 'compensated', 'failed')),
+    current_step INT NOT NULL DEFAULT 0,
+    completed_steps JSON NOT NULL DEFAULT (JSON_ARRAY()),
+    context JSON NOT NULL DEFAULT (JSON_OBJECT()),
+    error TEXT,
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    INDEX idx_sagas_active (status),
+    INDEX idx_sagas_type (saga_type, created_at DESC)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='Saga coordinator state; active rows are resumed on startup';
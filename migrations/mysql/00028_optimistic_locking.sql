@@ -0,0 +1,10 @@
+-- ============================================
+-- Migration: 00028_optimistic_locking.sql (MySQL / MariaDB)
+-- Description: Version columns for optimistic concurrency control on
+--              posts, settings, menus, and media metadata
+-- ============================================
+
+ALTER TABLE posts ADD COLUMN version BIGINT NOT NULL DEFAULT 1;
+ALTER TABLE options ADD COLUMN version BIGINT NOT NULL DEFAULT 1;
+ALTER TABLE menus ADD COLUMN version BIGINT NOT NULL DEFAULT 1;
+ALTER TABLE media ADD COLUMN version BIGINT NOT NULL DEFAULT 1;
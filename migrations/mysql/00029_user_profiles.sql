@@ -0,0 +1,9 @@
+-- ============================================
+-- Migration: 00029_user_profiles.sql (MySQL / MariaDB)
+-- Description: Profile columns read by public author pages; other
+--              profile fields live in users.meta under "profile"
+-- ============================================
+
+ALTER TABLE users
+    ADD COLUMN bio TEXT,
+    ADD COLUMN website VARCHAR(500);
@@ -0,0 +1,23 @@
+-- ============================================
+-- Migration: 00030_saved_views.sql (MySQL / MariaDB)
+-- Description: Named filter/sort/column configurations for admin lists;
+--              per-user defaults and ordering live in users.meta under
+--              "list_views"
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS saved_views (
+    id CHAR(36) PRIMARY KEY,
+    owner_id CHAR(36) NOT NULL,
+    resource VARCHAR(20) NOT NULL CHECK (resource IN ('posts', 'comments', 'media')),
+    name VARCHAR(100) NOT NULL,
+    filter TEXT,
+    sort_by VARCHAR(50),
+    sort_order VARCHAR(4) CHECK (sort_order IN ('asc', 'desc')),
+    columns JSON NOT NULL DEFAULT (JSON_ARRAY()),
+    shared_roles JSON NOT NULL DEFAULT (JSON_ARRAY()),
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    UNIQUE KEY uq_saved_views_name (owner_id, resource, name),
+    CONSTRAINT fk_saved_views_owner FOREIGN KEY (owner_id) REFERENCES users(id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='Saved admin list views, visible to their owner and the roles in shared_roles';
@@ -0,0 +1,18 @@
+-- ============================================
+-- Migration: 00031_admin_search_indexes.sql (MySQL / MariaDB)
+-- Description: Prefix indexes for the admin command palette search. The
+--              utf8mb4_unicode_ci collation is case-insensitive, so plain
+--              (prefix-length) column indexes serve `LIKE 'term%'`. Also
+--              adds the posts.deleted_at column the trash code relies on.
+-- ============================================
+
+ALTER TABLE posts
+    ADD COLUMN deleted_at DATETIME(6),
+    ADD INDEX idx_posts_admin_search (post_type, title(191));
+
+ALTER TABLE media
+    ADD INDEX idx_media_admin_search_title (title(191)),
+    ADD INDEX idx_media_admin_search_filename (original_filename(191));
+
+ALTER TABLE users
+    ADD INDEX idx_users_admin_search_display_name (display_name(191));
@@ -0,0 +1,49 @@
+-- ============================================
+-- Migration: 00032_public_api.sql (MySQL / MariaDB)
+-- Description: Keys for the read-only public API tier and the rollup
+--              tables its per-key usage is flushed into
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS public_api_keys (
+    id CHAR(36) PRIMARY KEY,
+    name VARCHAR(100) NOT NULL,
+    key_prefix VARCHAR(16) NOT NULL,
+    key_hash CHAR(64) NOT NULL,
+    owner_id CHAR(36),
+    requests_per_minute INTEGER NOT NULL DEFAULT 60 CHECK (requests_per_minute > 0),
+    daily_quota INTEGER CHECK (daily_quota IS NULL OR daily_quota > 0),
+    allowed_origins JSON NOT NULL DEFAULT (JSON_ARRAY()),
+    last_used_at DATETIME(6),
+    revoked_at DATETIME(6),
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    UNIQUE KEY uq_public_api_keys_hash (key_hash),
+    CONSTRAINT fk_public_api_keys_owner FOREIGN KEY (owner_id) REFERENCES users(id) ON DELETE SET NULL
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='Read-only public API keys; only the SHA-256 hash of the secret is stored';
+
+CREATE TABLE IF NOT EXISTS public_api_usage_hourly (
+    key_id CHAR(36) NOT NULL,
+    hour DATETIME(6) NOT NULL,
+    requests BIGINT NOT NULL DEFAULT 0,
+    bytes_out BIGINT NOT NULL DEFAULT 0,
+    errors BIGINT NOT NULL DEFAULT 0,
+    rate_limited BIGINT NOT NULL DEFAULT 0,
+    cache_hits BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (key_id, hour),
+    INDEX idx_public_api_usage_hourly_hour (hour),
+    CONSTRAINT fk_public_api_usage_hourly_key FOREIGN KEY (key_id) REFERENCES public_api_keys(id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='Public API requests, bandwidth, errors and rate limiting per key and hour';
+
+CREATE TABLE IF NOT EXISTS public_api_endpoint_usage_daily (
+    key_id CHAR(36) NOT NULL,
+    day DATE NOT NULL,
+    endpoint VARCHAR(200) NOT NULL,
+    requests BIGINT NOT NULL DEFAULT 0,
+    bytes_out BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (key_id, day, endpoint),
+    INDEX idx_public_api_endpoint_usage_day (day),
+    CONSTRAINT fk_public_api_endpoint_usage_key FOREIGN KEY (key_id) REFERENCES public_api_keys(id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='Public API requests and bandwidth per key, day and route';
@@ -0,0 +1,26 @@
+-- ============================================
+-- Migration: 00033_http_signatures.sql (MySQL / MariaDB)
+-- Description: Keys for HTTP message signatures (RFC 9421): public keys of
+--              trusted integrations and the site's own signing keys
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS http_signature_keys (
+    id CHAR(36) PRIMARY KEY,
+    key_id VARCHAR(255) NOT NULL,
+    name VARCHAR(100) NOT NULL,
+    direction VARCHAR(16) NOT NULL CHECK (direction IN ('inbound', 'outbound')),
+    algorithm VARCHAR(32) NOT NULL,
+    public_key VARBINARY(1024) NOT NULL,
+    private_key VARBINARY(1024),
+    created_by CHAR(36),
+    expires_at DATETIME(6),
+    last_used_at DATETIME(6),
+    revoked_at DATETIME(6),
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    UNIQUE KEY uq_http_signature_keys_key_id (key_id),
+    INDEX idx_http_signature_keys_direction (direction, created_at),
+    CHECK (direction = 'outbound' OR private_key IS NULL),
+    CONSTRAINT fk_http_signature_keys_creator FOREIGN KEY (created_by) REFERENCES users(id) ON DELETE SET NULL
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='HTTP message signature keys; outbound private keys are stored sealed with a key derived from the site secret';
@@ -0,0 +1,21 @@
+-- ============================================
+-- Migration: 00034_content_sanitization.sql (MySQL / MariaDB)
+-- Description: Reports of the tags, attributes and comments the content
+--              sanitization policy stripped from stored HTML
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS content_sanitization_reports (
+    id CHAR(36) PRIMARY KEY,
+    source VARCHAR(20) NOT NULL CHECK (source IN ('post', 'comment', 'legacy')),
+    subject_id CHAR(36),
+    user_id CHAR(36),
+    policy VARCHAR(100) NOT NULL,
+    removed_tags JSON NOT NULL DEFAULT (JSON_OBJECT()),
+    removed_attributes JSON NOT NULL DEFAULT (JSON_OBJECT()),
+    removed_comments INTEGER NOT NULL DEFAULT 0,
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    INDEX idx_content_sanitization_reports_subject (source, subject_id, created_at),
+    INDEX idx_content_sanitization_reports_created (created_at),
+    CONSTRAINT fk_content_sanitization_reports_user FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE SET NULL
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='What the sanitization policy stripped from saved posts, comments and legacy HTML';
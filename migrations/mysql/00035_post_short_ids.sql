@@ -0,0 +1,9 @@
+-- ============================================
+-- Migration: 00035_post_short_ids.sql (MySQL / MariaDB)
+-- Description: Numeric post ids for `/?p=<id>` shortlinks; existing posts
+--              are numbered when the column is added
+-- ============================================
+
+ALTER TABLE posts
+    ADD COLUMN short_id BIGINT NOT NULL AUTO_INCREMENT UNIQUE
+    COMMENT 'Numeric id used by /?p= shortlinks';
@@ -0,0 +1,17 @@
+-- ============================================
+-- Migration: 00036_extension_allowlists.sql (MySQL / MariaDB)
+-- Description: Theme and plugin allowlists for the network, each tenant
+--              and each site; a NULL list leaves that kind unrestricted
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS extension_allowlists (
+    scope VARCHAR(20) NOT NULL CHECK (scope IN ('network', 'tenant', 'site')),
+    scope_id VARCHAR(100) NOT NULL DEFAULT '',
+    themes JSON,
+    plugins JSON,
+    updated_by CHAR(36),
+    updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    PRIMARY KEY (scope, scope_id),
+    CONSTRAINT fk_extension_allowlists_user FOREIGN KEY (updated_by) REFERENCES users(id) ON DELETE SET NULL
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='Themes and plugins each network, tenant or site may install and activate';
@@ -0,0 +1,22 @@
+-- ============================================
+-- Migration: 00037_job_schedules.sql (MySQL / MariaDB)
+-- Description: Cron schedules defined by site owners, with the time each
+--              last ran and is next due so missed runs can be caught up
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS job_schedules (
+    name VARCHAR(255) PRIMARY KEY,
+    expression VARCHAR(255) NOT NULL,
+    timezone VARCHAR(64) NOT NULL DEFAULT 'UTC',
+    catch_up VARCHAR(20) NOT NULL DEFAULT 'run_once' CHECK (catch_up IN ('skip', 'run_once', 'run_all')),
+    catch_up_max INT,
+    job_type VARCHAR(255) NOT NULL,
+    queue VARCHAR(100) NOT NULL DEFAULT 'default',
+    payload JSON NOT NULL DEFAULT (JSON_OBJECT()),
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    last_run_at DATETIME(6),
+    next_run_at DATETIME(6),
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='Cron schedules loaded by the job scheduler on startup';
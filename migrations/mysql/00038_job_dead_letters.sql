@@ -0,0 +1,21 @@
+-- ============================================
+-- Migration: 00038_job_dead_letters.sql (MySQL / MariaDB)
+-- Description: Jobs that ran out of retry attempts, kept for an
+--              administrator to inspect, requeue or discard
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS job_dead_letters (
+    id CHAR(36) PRIMARY KEY,
+    tenant_id CHAR(36),
+    queue VARCHAR(100) NOT NULL,
+    job_type VARCHAR(255) NOT NULL,
+    payload JSON NOT NULL DEFAULT (JSON_OBJECT()),
+    priority INT NOT NULL DEFAULT 0,
+    attempts INT NOT NULL DEFAULT 0,
+    max_attempts INT NOT NULL DEFAULT 3,
+    error TEXT NOT NULL,
+    job_created_at DATETIME(6) NOT NULL,
+    failed_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    INDEX idx_job_dead_letters_queue (queue, failed_at DESC)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='Failed jobs moved out of the jobs table once their retry policy gives up';
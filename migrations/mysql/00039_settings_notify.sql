@@ -0,0 +1,8 @@
+-- ============================================
+-- Migration: 00039_settings_notify.sql (MySQL / MariaDB)
+-- Description: MySQL has no LISTEN/NOTIFY, so settings changes are not
+--              announced across nodes; each node keeps its cached copy
+--              until restart. Kept so the version numbers line up.
+-- ============================================
+
+DO 0;
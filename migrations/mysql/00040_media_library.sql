@@ -0,0 +1,36 @@
+-- ============================================
+-- Migration: 00040_media_library.sql (MySQL / MariaDB)
+-- Description: Folder details and counts, tags and usage tracking for
+--              the media library. Folder item counts and sizes are
+--              kept up to date by triggers on PostgreSQL only.
+-- ============================================
+
+ALTER TABLE media_folders
+    ADD COLUMN slug VARCHAR(255) NOT NULL DEFAULT '',
+    ADD COLUMN description TEXT,
+    ADD COLUMN user_id CHAR(36),
+    ADD COLUMN color VARCHAR(20) NOT NULL DEFAULT '#6366f1',
+    ADD COLUMN icon VARCHAR(50) NOT NULL DEFAULT 'folder',
+    ADD COLUMN item_count INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN total_size BIGINT NOT NULL DEFAULT 0,
+    ADD COLUMN is_system BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN sort_order INTEGER NOT NULL DEFAULT 0,
+    ADD CONSTRAINT fk_media_folders_user
+        FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE SET NULL;
+
+ALTER TABLE media
+    ADD COLUMN tags JSON NOT NULL DEFAULT (JSON_ARRAY()),
+    ADD INDEX idx_media_folder_id (folder_id);
+
+CREATE TABLE IF NOT EXISTS media_usage (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    media_id CHAR(36) NOT NULL,
+    entity_type VARCHAR(50) NOT NULL,
+    entity_id CHAR(36) NOT NULL,
+    context VARCHAR(50) NOT NULL DEFAULT 'content',
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    UNIQUE KEY uq_media_usage (media_id, entity_type, entity_id, context),
+    INDEX idx_media_usage_entity (entity_type, entity_id),
+    CONSTRAINT fk_media_usage_media FOREIGN KEY (media_id) REFERENCES media(id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='Posts, pages and other content that reference each media item';
@@ -0,0 +1,61 @@
+-- ============================================
+-- Migration: 00041_comments.sql (MySQL / MariaDB)
+-- Description: Threading, moderation and spam columns for comments,
+--              comment meta, and per-post comment settings. Depths and
+--              reply, like and post comment counts are kept up to date
+--              by triggers on PostgreSQL only.
+-- ============================================
+
+ALTER TABLE comments
+    ADD COLUMN site_id CHAR(36),
+    ADD COLUMN depth INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN replies_count INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN user_id CHAR(36),
+    ADD COLUMN author_ip VARCHAR(45),
+    ADD COLUMN user_agent TEXT,
+    ADD COLUMN content_html TEXT,
+    ADD COLUMN is_edited BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN edited_at DATETIME(6),
+    ADD COLUMN moderated_by CHAR(36),
+    ADD COLUMN moderated_at DATETIME(6),
+    ADD COLUMN moderation_note TEXT,
+    ADD COLUMN spam_score DOUBLE,
+    ADD COLUMN spam_reasons JSON,
+    ADD INDEX idx_comments_parent (parent_id),
+    ADD INDEX idx_comments_user (user_id),
+    ADD INDEX idx_comments_queue (status, created_at),
+    ADD CONSTRAINT fk_comments_user
+        FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE SET NULL,
+    ADD CONSTRAINT fk_comments_moderated_by
+        FOREIGN KEY (moderated_by) REFERENCES users(id) ON DELETE SET NULL;
+
+UPDATE comments SET user_id = author_id WHERE user_id IS NULL AND author_id IS NOT NULL;
+
+ALTER TABLE comment_likes
+    ADD UNIQUE KEY idx_comment_likes_user (comment_id, user_id);
+
+CREATE TABLE IF NOT EXISTS comment_meta (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    comment_id CHAR(36) NOT NULL,
+    meta_key VARCHAR(255) NOT NULL,
+    meta_value JSON,
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    UNIQUE KEY uq_comment_meta (comment_id, meta_key),
+    CONSTRAINT fk_comment_meta_comment FOREIGN KEY (comment_id) REFERENCES comments(id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+ALTER TABLE posts
+    ADD COLUMN comment_status VARCHAR(50) DEFAULT 'open',
+    ADD COLUMN comment_count INTEGER DEFAULT 0;
+
+CREATE TABLE IF NOT EXISTS post_comment_settings (
+    post_id CHAR(36) PRIMARY KEY,
+    require_moderation BOOLEAN,
+    max_depth INTEGER,
+    closes_at DATETIME(6),
+    updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    CONSTRAINT chk_post_comment_settings_depth CHECK (max_depth IS NULL OR max_depth >= 1),
+    CONSTRAINT fk_post_comment_settings_post FOREIGN KEY (post_id) REFERENCES posts(id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='Moderation, threading depth and closing time overrides for a post''s comments';
@@ -0,0 +1,9 @@
+-- ============================================
+-- Migration: 00042_change_feed.sql (MySQL / MariaDB)
+-- Description: MySQL has no LISTEN/NOTIFY, so content writes are not
+--              announced; live views only see changes made through the
+--              node they are connected to. Kept so the version numbers
+--              line up.
+-- ============================================
+
+DO 0;
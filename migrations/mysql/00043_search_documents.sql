@@ -0,0 +1,23 @@
+-- ============================================
+-- Migration: 00043_search_documents.sql (MySQL / MariaDB)
+-- Description: Full-text search index for published posts, pages and
+--              media. Ranking uses a FULLTEXT index in place of the
+--              weighted tsvector used on PostgreSQL.
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS search_documents (
+    id CHAR(36) PRIMARY KEY,
+    kind VARCHAR(20) NOT NULL,
+    title TEXT NOT NULL,
+    excerpt TEXT,
+    body MEDIUMTEXT NOT NULL,
+    slug VARCHAR(500),
+    published_at DATETIME(6),
+    source_updated_at DATETIME(6) NOT NULL,
+    language VARCHAR(50) NOT NULL DEFAULT 'english',
+    indexed_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    INDEX idx_search_documents_kind (kind),
+    FULLTEXT INDEX idx_search_documents_text (title, excerpt, body),
+    CONSTRAINT chk_search_documents_kind CHECK (kind IN ('post', 'page', 'media'))
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='Full-text search index of published posts, pages and media';
@@ -0,0 +1,38 @@
+-- ============================================
+-- Migration: 00044_job_executions.sql (MySQL / MariaDB)
+-- Description: Audit trail of job attempts and per-job-type SLAs
+--              evaluated against it
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS job_executions (
+    id BIGINT UNSIGNED AUTO_INCREMENT PRIMARY KEY,
+    job_id CHAR(36) NOT NULL,
+    tenant_id CHAR(36),
+    queue VARCHAR(100) NOT NULL,
+    job_type VARCHAR(255) NOT NULL,
+    attempt INT NOT NULL,
+    outcome VARCHAR(20) NOT NULL CHECK (outcome IN ('completed', 'retried', 'failed')),
+    error TEXT,
+    available_at DATETIME(6) NOT NULL,
+    started_at DATETIME(6) NOT NULL,
+    finished_at DATETIME(6) NOT NULL,
+    queue_latency_ms BIGINT NOT NULL,
+    duration_ms BIGINT NOT NULL,
+    INDEX idx_job_executions_type (job_type, finished_at DESC),
+    INDEX idx_job_executions_finished (finished_at),
+    INDEX idx_job_executions_job (job_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='One row per job attempt, recorded by the worker when the attempt ends';
+
+CREATE TABLE IF NOT EXISTS job_slas (
+    job_type VARCHAR(255) PRIMARY KEY,
+    max_duration_secs BIGINT CHECK (max_duration_secs > 0),
+    max_queue_latency_secs BIGINT CHECK (max_queue_latency_secs > 0),
+    max_failure_rate DOUBLE CHECK (max_failure_rate >= 0 AND max_failure_rate <= 1),
+    min_executions INT NOT NULL DEFAULT 5,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    alerted_at DATETIME(6),
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6) ON UPDATE CURRENT_TIMESTAMP(6)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='Service levels per job type, checked by the evaluate_job_slas job';
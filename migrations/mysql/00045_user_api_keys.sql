@@ -0,0 +1,22 @@
+-- ============================================
+-- Migration: 00045_user_api_keys.sql (MySQL / MariaDB)
+-- Description: Scoped API keys that act as the user who created them, for
+--              scripts and running the CLI against a live site
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS user_api_keys (
+    id CHAR(36) PRIMARY KEY,
+    user_id CHAR(36) NOT NULL,
+    name VARCHAR(100) NOT NULL,
+    key_prefix VARCHAR(16) NOT NULL,
+    key_hash CHAR(64) NOT NULL,
+    scopes JSON NOT NULL DEFAULT (JSON_ARRAY('*:*')),
+    expires_at DATETIME(6),
+    last_used_at DATETIME(6),
+    revoked_at DATETIME(6),
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    UNIQUE KEY uq_user_api_keys_hash (key_hash),
+    KEY idx_user_api_keys_user (user_id, created_at),
+    CONSTRAINT fk_user_api_keys_user FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='Scoped API keys acting as their owner; only the SHA-256 hash of the secret is stored';
@@ -0,0 +1,25 @@
+-- ============================================
+-- Migration: 00046_user_imports.sql (MySQL / MariaDB)
+-- Description: Bulk user import runs with their progress, so admins and the
+--              CLI can follow an import while it is processed
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS user_imports (
+    id CHAR(36) PRIMARY KEY,
+    started_by CHAR(36),
+    status VARCHAR(20) NOT NULL DEFAULT 'running',
+    dry_run BOOLEAN NOT NULL DEFAULT FALSE,
+    total INT NOT NULL DEFAULT 0,
+    processed INT NOT NULL DEFAULT 0,
+    created INT NOT NULL DEFAULT 0,
+    updated INT NOT NULL DEFAULT 0,
+    skipped INT NOT NULL DEFAULT 0,
+    failed INT NOT NULL DEFAULT 0,
+    invited INT NOT NULL DEFAULT 0,
+    errors JSON NOT NULL DEFAULT (JSON_ARRAY()),
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    finished_at DATETIME(6),
+    KEY idx_user_imports_created (created_at),
+    CONSTRAINT fk_user_imports_started_by FOREIGN KEY (started_by) REFERENCES users(id) ON DELETE SET NULL
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='Bulk user import runs; rows are processed in the background and counters updated as they go';
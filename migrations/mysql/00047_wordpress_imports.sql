@@ -0,0 +1,46 @@
+-- ============================================
+-- Migration: 00047_wordpress_imports.sql (MySQL / MariaDB)
+-- Description: WordPress (WXR) import runs with their progress, and the
+--              redirects that keep old WordPress URLs working
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS wordpress_imports (
+    id CHAR(36) PRIMARY KEY,
+    started_by CHAR(36),
+    status VARCHAR(20) NOT NULL DEFAULT 'running',
+    dry_run BOOLEAN NOT NULL DEFAULT FALSE,
+    source_url VARCHAR(500),
+    total INT NOT NULL DEFAULT 0,
+    processed INT NOT NULL DEFAULT 0,
+    authors INT NOT NULL DEFAULT 0,
+    categories INT NOT NULL DEFAULT 0,
+    tags INT NOT NULL DEFAULT 0,
+    posts INT NOT NULL DEFAULT 0,
+    pages INT NOT NULL DEFAULT 0,
+    media INT NOT NULL DEFAULT 0,
+    comments INT NOT NULL DEFAULT 0,
+    redirects INT NOT NULL DEFAULT 0,
+    skipped INT NOT NULL DEFAULT 0,
+    failed INT NOT NULL DEFAULT 0,
+    errors JSON NOT NULL DEFAULT (JSON_ARRAY()),
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    finished_at DATETIME(6),
+    KEY idx_wordpress_imports_created (created_at),
+    CONSTRAINT fk_wordpress_imports_started_by FOREIGN KEY (started_by) REFERENCES users(id) ON DELETE SET NULL
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='WXR import runs; items are processed in the background and counters updated as they go';
+
+CREATE TABLE IF NOT EXISTS redirects (
+    id CHAR(36) PRIMARY KEY,
+    source_path VARCHAR(768) NOT NULL,
+    target VARCHAR(2048) NOT NULL,
+    status_code SMALLINT NOT NULL DEFAULT 301,
+    origin VARCHAR(50) NOT NULL DEFAULT 'manual',
+    import_id CHAR(36),
+    hits BIGINT NOT NULL DEFAULT 0,
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    UNIQUE KEY uq_redirects_source (source_path),
+    KEY idx_redirects_import (import_id),
+    CONSTRAINT fk_redirects_import FOREIGN KEY (import_id) REFERENCES wordpress_imports(id) ON DELETE SET NULL
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='Old paths redirected to their new location, e.g. WordPress permalinks after an import';
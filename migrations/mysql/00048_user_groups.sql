@@ -0,0 +1,40 @@
+-- ============================================
+-- Migration: 00048_user_groups.sql (MySQL / MariaDB)
+-- Description: User groups (teams) granting roles and capabilities to their
+--              members and owning content sections
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS user_groups (
+    id CHAR(36) PRIMARY KEY,
+    name VARCHAR(100) NOT NULL,
+    slug VARCHAR(100) NOT NULL,
+    description TEXT,
+    roles JSON NOT NULL DEFAULT (JSON_ARRAY()),
+    capabilities JSON NOT NULL DEFAULT (JSON_ARRAY()),
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6) ON UPDATE CURRENT_TIMESTAMP(6),
+    UNIQUE KEY uq_user_groups_slug (slug)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='Teams of users; members gain the group''s roles and capabilities';
+
+CREATE TABLE IF NOT EXISTS user_group_members (
+    group_id CHAR(36) NOT NULL,
+    user_id CHAR(36) NOT NULL,
+    is_manager BOOLEAN NOT NULL DEFAULT FALSE,
+    added_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    PRIMARY KEY (group_id, user_id),
+    KEY idx_user_group_members_user (user_id),
+    CONSTRAINT fk_user_group_members_group FOREIGN KEY (group_id) REFERENCES user_groups(id) ON DELETE CASCADE,
+    CONSTRAINT fk_user_group_members_user FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+CREATE TABLE IF NOT EXISTS user_group_sections (
+    group_id CHAR(36) NOT NULL,
+    category_id CHAR(36) NOT NULL,
+    added_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    PRIMARY KEY (group_id, category_id),
+    KEY idx_user_group_sections_category (category_id),
+    CONSTRAINT fk_user_group_sections_group FOREIGN KEY (group_id) REFERENCES user_groups(id) ON DELETE CASCADE,
+    CONSTRAINT fk_user_group_sections_category FOREIGN KEY (category_id) REFERENCES categories(id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='Categories, with their subcategories, whose posts only owning groups may change';
@@ -0,0 +1,15 @@
+-- ============================================
+-- Migration: 00049_redirect_rules.sql (MySQL / MariaDB)
+-- Description: Redirect manager rules: regex patterns besides exact paths,
+--              temporary redirects and 410 Gone, ordering and hit tracking
+-- ============================================
+
+ALTER TABLE redirects
+    ADD COLUMN match_type VARCHAR(10) NOT NULL DEFAULT 'exact'
+        COMMENT 'exact or regex; exact paths are checked before patterns',
+    ADD COLUMN position INT NOT NULL DEFAULT 0
+        COMMENT 'Order regex patterns are tried in, lowest first',
+    ADD COLUMN enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    ADD COLUMN last_hit_at DATETIME(6),
+    ADD COLUMN updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6) ON UPDATE CURRENT_TIMESTAMP(6),
+    ADD KEY idx_redirects_match (match_type, position);
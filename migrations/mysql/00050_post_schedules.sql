@@ -0,0 +1,19 @@
+-- ============================================
+-- Migration: 00050_post_schedules.sql (MySQL / MariaDB)
+-- Description: Scheduled unpublishing (expiry), embargo windows and the
+--              timezone a post's schedule was entered in
+-- ============================================
+
+ALTER TABLE posts
+    ADD COLUMN scheduled_at DATETIME(6),
+    ADD COLUMN expires_at DATETIME(6)
+        COMMENT 'When a published post goes back to draft',
+    ADD COLUMN embargo_starts_at DATETIME(6)
+        COMMENT 'Start of the period the post is withheld in; NULL with an end means right away',
+    ADD COLUMN embargo_ends_at DATETIME(6)
+        COMMENT 'When the embargo lifts and a withheld post is published again',
+    ADD COLUMN schedule_timezone VARCHAR(64)
+        COMMENT 'IANA timezone wall-clock schedule dates were entered in',
+    ADD KEY idx_posts_scheduled (scheduled_at),
+    ADD KEY idx_posts_expires (expires_at),
+    ADD KEY idx_posts_embargo (embargo_ends_at);
@@ -0,0 +1,36 @@
+-- ============================================
+-- Migration: 00051_editorial_discussions.sql (MySQL / MariaDB)
+-- Description: Internal discussion threads on drafts, anchored to editor
+--              blocks and kept apart from public comments
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS discussion_threads (
+    id CHAR(36) PRIMARY KEY,
+    post_id CHAR(36) NOT NULL,
+    block_client_id VARCHAR(64),
+    quoted_text TEXT,
+    status VARCHAR(20) NOT NULL DEFAULT 'open',
+    created_by CHAR(36),
+    resolved_by CHAR(36),
+    resolved_at DATETIME(6),
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6) ON UPDATE CURRENT_TIMESTAMP(6),
+    KEY idx_discussion_threads_post (post_id, status, created_at),
+    CONSTRAINT fk_discussion_threads_post FOREIGN KEY (post_id) REFERENCES posts(id) ON DELETE CASCADE,
+    CONSTRAINT fk_discussion_threads_creator FOREIGN KEY (created_by) REFERENCES users(id) ON DELETE SET NULL,
+    CONSTRAINT fk_discussion_threads_resolver FOREIGN KEY (resolved_by) REFERENCES users(id) ON DELETE SET NULL
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='Editorial discussions on drafts; never shown publicly';
+
+CREATE TABLE IF NOT EXISTS discussion_comments (
+    id CHAR(36) PRIMARY KEY,
+    thread_id CHAR(36) NOT NULL,
+    author_id CHAR(36),
+    content TEXT NOT NULL,
+    mentions JSON NOT NULL DEFAULT (JSON_ARRAY()),
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6) ON UPDATE CURRENT_TIMESTAMP(6),
+    KEY idx_discussion_comments_thread (thread_id, created_at),
+    CONSTRAINT fk_discussion_comments_thread FOREIGN KEY (thread_id) REFERENCES discussion_threads(id) ON DELETE CASCADE,
+    CONSTRAINT fk_discussion_comments_author FOREIGN KEY (author_id) REFERENCES users(id) ON DELETE SET NULL
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
@@ -0,0 +1,46 @@
+-- ============================================
+-- Migration: 00052_social_sharing.sql (MySQL / MariaDB)
+-- Description: Per-post social preview settings and the queue and log of
+--              shares to social network accounts
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS social_post_settings (
+    post_id CHAR(36) PRIMARY KEY,
+    card_title VARCHAR(200),
+    card_subtitle VARCHAR(200),
+    auto_post BOOLEAN NOT NULL DEFAULT TRUE,
+    messages JSON NOT NULL DEFAULT (JSON_OBJECT()),
+    updated_by CHAR(36),
+    updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6) ON UPDATE CURRENT_TIMESTAMP(6),
+    CONSTRAINT fk_social_post_settings_post FOREIGN KEY (post_id) REFERENCES posts(id) ON DELETE CASCADE,
+    CONSTRAINT fk_social_post_settings_user FOREIGN KEY (updated_by) REFERENCES users(id) ON DELETE SET NULL
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='Preview card and message overrides of posts';
+
+CREATE TABLE IF NOT EXISTS social_shares (
+    id CHAR(36) PRIMARY KEY,
+    post_id CHAR(36) NOT NULL,
+    account_id VARCHAR(64) NOT NULL,
+    network VARCHAR(20) NOT NULL,
+    message TEXT,
+    automatic BOOLEAN NOT NULL DEFAULT FALSE,
+    -- Set only for automatic shares, so the unique key allows one per account
+    automatic_key VARCHAR(64) GENERATED ALWAYS AS (CASE WHEN automatic THEN account_id END) STORED,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending',
+    scheduled_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    attempts INT NOT NULL DEFAULT 0,
+    remote_id VARCHAR(255),
+    remote_url TEXT,
+    last_error TEXT,
+    log JSON NOT NULL DEFAULT (JSON_ARRAY()),
+    created_by CHAR(36),
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6) ON UPDATE CURRENT_TIMESTAMP(6),
+    sent_at DATETIME(6),
+    UNIQUE KEY idx_social_shares_automatic (post_id, automatic_key),
+    KEY idx_social_shares_due (status, scheduled_at),
+    KEY idx_social_shares_post (post_id, created_at),
+    CONSTRAINT fk_social_shares_post FOREIGN KEY (post_id) REFERENCES posts(id) ON DELETE CASCADE,
+    CONSTRAINT fk_social_shares_creator FOREIGN KEY (created_by) REFERENCES users(id) ON DELETE SET NULL
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='Messages about posts to social accounts, queued, sent or failed';
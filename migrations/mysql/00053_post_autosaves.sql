@@ -0,0 +1,18 @@
+-- ============================================
+-- Migration: 00053_post_autosaves.sql (MySQL / MariaDB)
+-- Description: Snapshots of posts being edited together, taken while the
+--              editing session runs and kept apart from saved revisions
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS post_autosaves (
+    id CHAR(36) PRIMARY KEY,
+    post_id CHAR(36) NOT NULL,
+    version BIGINT NOT NULL,
+    title VARCHAR(500) NOT NULL,
+    content LONGTEXT NOT NULL,
+    contributors JSON NOT NULL DEFAULT (JSON_ARRAY()),
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    KEY idx_post_autosaves_post (post_id, created_at),
+    CONSTRAINT fk_post_autosaves_post FOREIGN KEY (post_id) REFERENCES posts(id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='Autosaves of collaborative editing sessions; the newest 20 are kept per post';
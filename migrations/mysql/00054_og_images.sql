@@ -0,0 +1,16 @@
+-- ============================================
+-- Migration: 00054_og_images.sql (MySQL / MariaDB)
+-- Description: Rasterized Open Graph images of posts, kept in storage
+--              until the preview card they were made from changes
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS og_images (
+    post_id CHAR(36) PRIMARY KEY,
+    svg_hash VARCHAR(64) NOT NULL COMMENT 'SHA-256 of the card SVG the image was rasterized from',
+    path VARCHAR(500) NOT NULL COMMENT 'Storage path of the image',
+    content_type VARCHAR(100) NOT NULL,
+    size BIGINT NOT NULL DEFAULT 0,
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    CONSTRAINT fk_og_images_post FOREIGN KEY (post_id) REFERENCES posts(id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='Generated og:image of each post; files live under og-images/{post_id}/ in storage';
@@ -0,0 +1,42 @@
+-- ============================================
+-- Migration: 00055_podcasting.sql (MySQL / MariaDB)
+-- Description: Podcast episodes (posts of type 'episode') with their audio
+--              enclosures, and a daily log of episode downloads
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS podcast_episodes (
+    post_id CHAR(36) PRIMARY KEY,
+    media_id CHAR(36) NULL COMMENT 'Uploaded audio; NULL when audio_url points elsewhere',
+    audio_url TEXT NULL,
+    mime_type VARCHAR(100) NOT NULL,
+    file_size BIGINT NOT NULL DEFAULT 0,
+    duration_seconds INT NOT NULL,
+    season INT NULL,
+    episode_number INT NULL,
+    episode_type VARCHAR(20) NOT NULL DEFAULT 'full' COMMENT 'full, trailer or bonus, as itunes:episodeType',
+    explicit BOOLEAN NULL COMMENT 'Overrides the show''s explicit flag when set',
+    transcript_url TEXT NULL,
+    updated_by CHAR(36) NULL,
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    CONSTRAINT fk_podcast_episodes_post FOREIGN KEY (post_id) REFERENCES posts(id) ON DELETE CASCADE,
+    CONSTRAINT fk_podcast_episodes_media FOREIGN KEY (media_id) REFERENCES media(id) ON DELETE SET NULL,
+    CONSTRAINT fk_podcast_episodes_user FOREIGN KEY (updated_by) REFERENCES users(id) ON DELETE SET NULL
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='Audio enclosure of each podcast episode';
+
+CREATE TABLE IF NOT EXISTS podcast_downloads (
+    post_id CHAR(36) NOT NULL,
+    day DATE NOT NULL,
+    listener_hash VARCHAR(64) NOT NULL COMMENT 'SHA-256 of address, user agent and day',
+    app VARCHAR(50) NOT NULL,
+    requests INT NOT NULL DEFAULT 1,
+    bytes_served BIGINT NOT NULL DEFAULT 0,
+    counted BOOLEAN NOT NULL DEFAULT FALSE COMMENT 'Whether enough audio was served to count as a download',
+    first_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    last_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    PRIMARY KEY (post_id, day, listener_hash),
+    INDEX idx_podcast_downloads_day (day),
+    CONSTRAINT fk_podcast_downloads_post FOREIGN KEY (post_id) REFERENCES posts(id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='Audio requests per episode, day and anonymous listener';
@@ -0,0 +1,31 @@
+-- ============================================
+-- Migration: 00056_events.sql (MySQL / MariaDB)
+-- Description: Event details of posts of type 'event': schedule with
+--              timezone and recurrence rule, venue, organizer and tickets
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS event_details (
+    post_id CHAR(36) PRIMARY KEY,
+    starts_at DATETIME(6) NOT NULL COMMENT 'Start of the first occurrence',
+    ends_at DATETIME(6) NOT NULL,
+    all_day BOOLEAN NOT NULL DEFAULT FALSE,
+    timezone VARCHAR(64) NOT NULL DEFAULT 'UTC' COMMENT 'IANA timezone the schedule and recurrence rule are in',
+    rrule TEXT NULL COMMENT 'RFC 5545 recurrence rule; NULL for one-off events',
+    exdates JSON NOT NULL COMMENT 'Local start times of cancelled occurrences',
+    series_ends_at DATETIME(6) NULL COMMENT 'End of the last occurrence; NULL when the series never ends',
+    venue JSON NULL,
+    online_url TEXT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'scheduled',
+    organizer_name VARCHAR(255) NULL,
+    organizer_url TEXT NULL,
+    ticket_url TEXT NULL,
+    price VARCHAR(50) NULL,
+    currency CHAR(3) NULL,
+    updated_by CHAR(36) NULL,
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    INDEX idx_event_details_schedule (starts_at, series_ends_at),
+    CONSTRAINT fk_event_details_post FOREIGN KEY (post_id) REFERENCES posts(id) ON DELETE CASCADE,
+    CONSTRAINT fk_event_details_user FOREIGN KEY (updated_by) REFERENCES users(id) ON DELETE SET NULL
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='Schedule, venue and tickets of each event';
@@ -0,0 +1,101 @@
+-- ============================================
+-- Migration: 00057_taxonomies.sql (MySQL / MariaDB)
+-- Description: Taxonomy registry with hierarchical terms, term meta and
+--              term relationships; categories and tags move into it
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS taxonomies (
+    id CHAR(36) PRIMARY KEY,
+    slug VARCHAR(32) NOT NULL,
+    name VARCHAR(100) NOT NULL,
+    singular_name VARCHAR(100) NULL,
+    description TEXT NULL,
+    hierarchical BOOLEAN NOT NULL DEFAULT FALSE,
+    public BOOLEAN NOT NULL DEFAULT TRUE,
+    rewrite_slug VARCHAR(64) NULL COMMENT 'First segment of term archive URLs; NULL uses the slug',
+    post_types JSON NOT NULL COMMENT 'Post types whose posts can carry terms of the taxonomy',
+    is_system BOOLEAN NOT NULL DEFAULT FALSE COMMENT 'Built-in taxonomies cannot be deleted',
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    UNIQUE KEY uq_taxonomies_slug (slug),
+    UNIQUE KEY uq_taxonomies_rewrite_slug (rewrite_slug)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='Registered taxonomies: built-in categories and tags, and custom ones';
+
+CREATE TABLE IF NOT EXISTS terms (
+    id CHAR(36) PRIMARY KEY,
+    taxonomy_id CHAR(36) NOT NULL,
+    parent_id CHAR(36) NULL,
+    name VARCHAR(255) NOT NULL,
+    slug VARCHAR(255) NOT NULL,
+    description TEXT NULL,
+    term_order INT NOT NULL DEFAULT 0,
+    count INT NOT NULL DEFAULT 0 COMMENT 'Published posts carrying the term',
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    UNIQUE KEY terms_taxonomy_slug_unique (taxonomy_id, slug),
+    INDEX idx_terms_parent (parent_id),
+    CONSTRAINT fk_terms_taxonomy FOREIGN KEY (taxonomy_id) REFERENCES taxonomies(id) ON DELETE CASCADE,
+    CONSTRAINT fk_terms_parent FOREIGN KEY (parent_id) REFERENCES terms(id) ON DELETE SET NULL
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='Terms of each taxonomy; only hierarchical taxonomies have parents';
+
+CREATE TABLE IF NOT EXISTS term_meta (
+    term_id CHAR(36) NOT NULL,
+    meta_key VARCHAR(191) NOT NULL,
+    meta_value JSON NOT NULL,
+    PRIMARY KEY (term_id, meta_key),
+    CONSTRAINT fk_term_meta_term FOREIGN KEY (term_id) REFERENCES terms(id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='Arbitrary JSON values attached to terms';
+
+CREATE TABLE IF NOT EXISTS term_relationships (
+    object_id CHAR(36) NOT NULL,
+    object_type VARCHAR(20) NOT NULL DEFAULT 'post',
+    term_id CHAR(36) NOT NULL,
+    term_order INT NOT NULL DEFAULT 0,
+    PRIMARY KEY (object_id, object_type, term_id),
+    INDEX idx_term_relationships_term (term_id, object_type),
+    CONSTRAINT fk_term_relationships_term FOREIGN KEY (term_id) REFERENCES terms(id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='Terms carried by posts and other objects';
+
+-- Built-in taxonomies
+INSERT IGNORE INTO taxonomies (id, slug, name, singular_name, hierarchical, rewrite_slug, post_types, is_system)
+VALUES
+    (UUID(), 'category', 'Categories', 'Category', TRUE, 'category', '["post", "episode"]', TRUE),
+    (UUID(), 'post_tag', 'Tags', 'Tag', FALSE, 'tag', '["post", "episode"]', TRUE);
+
+-- Move the legacy categories and tags over, keeping their ids. Parents are
+-- linked afterwards since foreign keys are checked row by row
+INSERT IGNORE INTO terms (id, taxonomy_id, name, slug, description, created_at, updated_at)
+SELECT c.id, tx.id, c.name, c.slug, c.description, c.created_at, c.updated_at
+FROM categories c
+JOIN taxonomies tx ON tx.slug = 'category';
+
+UPDATE terms t
+JOIN categories c ON c.id = t.id
+SET t.parent_id = c.parent_id
+WHERE c.parent_id IS NOT NULL;
+
+INSERT IGNORE INTO terms (id, taxonomy_id, name, slug, description, created_at, updated_at)
+SELECT t.id, tx.id, t.name, t.slug, t.description, t.created_at, t.updated_at
+FROM tags t
+JOIN taxonomies tx ON tx.slug = 'post_tag';
+
+INSERT IGNORE INTO term_relationships (object_id, object_type, term_id)
+SELECT pc.post_id, 'post', pc.category_id
+FROM post_categories pc
+JOIN terms t ON t.id = pc.category_id;
+
+INSERT IGNORE INTO term_relationships (object_id, object_type, term_id)
+SELECT pt.post_id, 'post', pt.tag_id
+FROM post_tags pt
+JOIN terms t ON t.id = pt.tag_id;
+
+UPDATE terms t SET count = (
+    SELECT COUNT(*) FROM term_relationships tr
+    JOIN posts p ON p.id = tr.object_id
+    WHERE tr.term_id = t.id AND tr.object_type = 'post'
+      AND p.status = 'published' AND p.deleted_at IS NULL
+);
@@ -0,0 +1,42 @@
+-- ============================================
+-- Migration: 00058_navigation_menus.sql (MySQL / MariaDB)
+-- Description: Menu items link to posts, terms or custom URLs and carry
+--              visibility rules; menus are assigned to theme locations
+-- ============================================
+
+ALTER TABLE menus ADD COLUMN description TEXT NULL;
+
+ALTER TABLE menus MODIFY COLUMN location VARCHAR(100) NULL
+    COMMENT 'Location the menu fills when the active theme has no assignment for it';
+
+UPDATE menu_items SET object_type = 'custom' WHERE object_type IS NULL;
+ALTER TABLE menu_items MODIFY COLUMN object_type VARCHAR(50) NOT NULL DEFAULT 'custom'
+    COMMENT 'custom (url), post (object_id is a post of any type) or term (object_id is a term)';
+
+ALTER TABLE menu_items
+    ADD COLUMN visibility VARCHAR(20) NOT NULL DEFAULT 'everyone'
+        COMMENT 'everyone, logged_in, logged_out or roles',
+    ADD COLUMN roles JSON NULL
+        COMMENT 'Roles that see the item when visibility is roles';
+
+UPDATE menu_items SET roles = JSON_ARRAY() WHERE roles IS NULL;
+ALTER TABLE menu_items MODIFY COLUMN roles JSON NOT NULL;
+
+CREATE INDEX idx_menu_items_menu_order ON menu_items(menu_id, menu_order);
+
+-- MySQL treats NULL site ids as distinct, so the single-site key uses a
+-- generated column
+CREATE TABLE IF NOT EXISTS theme_menu_assignments (
+    id CHAR(36) PRIMARY KEY,
+    site_id CHAR(36) NULL,
+    site_key CHAR(36) AS (COALESCE(site_id, '')) STORED,
+    theme_id VARCHAR(100) NOT NULL,
+    location_slug VARCHAR(100) NOT NULL,
+    menu_id CHAR(36) NOT NULL,
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    UNIQUE KEY theme_menu_assignments_location_unique (site_key, theme_id, location_slug),
+    INDEX idx_theme_menu_assignments_menu (menu_id),
+    CONSTRAINT fk_theme_menu_assignments_menu FOREIGN KEY (menu_id) REFERENCES menus(id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='Menu shown in each location of a theme';
@@ -0,0 +1,41 @@
+-- ============================================
+-- Migration: 00059_widget_areas.sql (MySQL / MariaDB)
+-- Description: Widget areas registered from theme manifests; widgets are
+--              blocks placed into them
+-- ============================================
+
+-- MySQL treats NULL site ids as distinct, so the single-site key uses a
+-- generated column
+CREATE TABLE IF NOT EXISTS widget_areas (
+    id CHAR(36) PRIMARY KEY,
+    site_id CHAR(36) NULL,
+    site_key CHAR(36) AS (COALESCE(site_id, '')) STORED,
+    theme VARCHAR(100) NOT NULL,
+    slug VARCHAR(100) NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    description TEXT NULL,
+    before_widget TEXT NULL COMMENT 'Markup opening each widget; {id} and {class} are replaced with the widget''s',
+    after_widget TEXT NULL,
+    before_title TEXT NULL,
+    after_title TEXT NULL,
+    position INT NOT NULL DEFAULT 0 COMMENT 'Order of the area in the manifest',
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    deleted_at DATETIME(6) NULL COMMENT 'Set when the theme stops declaring the area; its widgets come back with it',
+    UNIQUE KEY widget_areas_theme_slug_unique (site_key, theme, slug)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='Widget areas each theme declares in its manifest';
+
+ALTER TABLE widgets
+    ADD COLUMN area_id CHAR(36) NULL,
+    ADD COLUMN deleted_at DATETIME(6) NULL,
+    MODIFY COLUMN sidebar VARCHAR(100) NULL
+        COMMENT 'Area slug of widgets placed before areas were registered; the first area registered with that slug adopts them',
+    MODIFY COLUMN widget_type VARCHAR(100) NOT NULL
+        COMMENT 'Block name such as core/paragraph, or a classic widget: search, recent_posts, categories, tag_cloud, navigation, text or html',
+    MODIFY COLUMN content TEXT NULL
+        COMMENT 'Inner HTML of blocks, or their inner blocks as JSON; body of text and html widgets',
+    MODIFY COLUMN settings JSON DEFAULT (JSON_OBJECT())
+        COMMENT 'Block attributes, or the options of classic widgets',
+    ADD INDEX idx_widgets_area (area_id, widget_order),
+    ADD CONSTRAINT fk_widgets_area FOREIGN KEY (area_id) REFERENCES widget_areas(id) ON DELETE CASCADE;
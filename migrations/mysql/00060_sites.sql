@@ -0,0 +1,38 @@
+-- ============================================
+-- Migration: 00060_sites.sql (MySQL / MariaDB)
+-- Description: Sites of a network; each has its own theme and plugin
+--              activation and can be suspended by network admins
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS sites (
+    id CHAR(36) PRIMARY KEY,
+    slug VARCHAR(63) NOT NULL
+        COMMENT 'Subdomain or first path segment the site is served under',
+    name VARCHAR(255) NOT NULL,
+    domain VARCHAR(253) NULL
+        COMMENT 'Custom domain; always resolves to the site',
+    tenant VARCHAR(100) NULL
+        COMMENT 'Tenant whose extension allowlist applies; NULL uses the slug',
+    status VARCHAR(20) NOT NULL DEFAULT 'active'
+        COMMENT 'active, suspended (answers 503) or archived (answers 410)',
+    status_reason TEXT NULL,
+    owner_id CHAR(36) NULL,
+    status_changed_at DATETIME(6) NULL,
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    UNIQUE KEY sites_slug_unique (slug),
+    UNIQUE KEY sites_domain_unique (domain),
+    INDEX idx_sites_tenant (tenant),
+    CONSTRAINT chk_sites_status CHECK (status IN ('active', 'suspended', 'archived')),
+    CONSTRAINT fk_sites_owner FOREIGN KEY (owner_id) REFERENCES users(id) ON DELETE SET NULL
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='Sites of the network besides the main site, whose rows have no site_id';
+
+CREATE TABLE IF NOT EXISTS site_plugins (
+    site_id CHAR(36) NOT NULL,
+    plugin_id VARCHAR(100) NOT NULL,
+    activated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    PRIMARY KEY (site_id, plugin_id),
+    CONSTRAINT fk_site_plugins_site FOREIGN KEY (site_id) REFERENCES sites(id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='Plugins active on one site; network-active plugins run on every site';
@@ -0,0 +1,30 @@
+-- ============================================
+-- Migration: 00061_site_usage.sql (MySQL / MariaDB)
+-- Description: Plans and quotas of network sites, and the storage,
+--              bandwidth and job time each uses per month
+-- ============================================
+
+ALTER TABLE sites
+    ADD COLUMN plan VARCHAR(50) NULL
+        COMMENT 'free, starter, professional, enterprise or a custom plan; NULL is unlimited',
+    ADD COLUMN quotas JSON NULL
+        COMMENT 'Quotas of the site alone, replacing those of its plan';
+
+CREATE TABLE IF NOT EXISTS site_usage (
+    site_id CHAR(36) NOT NULL,
+    period DATE NOT NULL
+        COMMENT 'First day of the month',
+    storage_bytes BIGINT NOT NULL DEFAULT 0
+        COMMENT 'Bytes kept at the end of the month; carried over to the next',
+    media_count BIGINT NOT NULL DEFAULT 0
+        COMMENT 'Files kept at the end of the month; carried over to the next',
+    bandwidth_bytes BIGINT NOT NULL DEFAULT 0
+        COMMENT 'Bytes of responses served during the month',
+    job_seconds BIGINT NOT NULL DEFAULT 0
+        COMMENT 'Seconds background jobs of the site ran during the month',
+    updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    PRIMARY KEY (site_id, period),
+    INDEX idx_site_usage_period (period),
+    CONSTRAINT fk_site_usage_site FOREIGN KEY (site_id) REFERENCES sites(id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='Usage of network sites by calendar month, for billing';
@@ -0,0 +1,17 @@
+-- ============================================
+-- Migration: 00062_plugin_settings.sql (MySQL / MariaDB)
+-- Description: Saved plugin settings for the installation and each
+--              tenant, versioned for optimistic locking
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS plugin_settings (
+    plugin_id VARCHAR(100) NOT NULL,
+    tenant_id VARCHAR(100) NOT NULL DEFAULT ''
+        COMMENT 'Empty for the settings of the whole installation',
+    version BIGINT NOT NULL DEFAULT 1
+        COMMENT 'Incremented by every save',
+    settings JSON NOT NULL,
+    updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    PRIMARY KEY (plugin_id, tenant_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='Settings of each plugin, validated against its configuration schema';
@@ -0,0 +1,51 @@
+-- ============================================
+-- Migration: 00063_setting_change_requests.sql (MySQL / MariaDB)
+-- Description: Four-eyes approval of sensitive settings: pending change
+--              requests and the audit trail of what happened to them
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS setting_change_requests (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    status VARCHAR(20) NOT NULL DEFAULT 'pending'
+        COMMENT 'pending, applied, rejected, cancelled, expired or superseded',
+    changes JSON NOT NULL
+        COMMENT 'Each setting with its value and version when requested and the proposed value',
+    reason TEXT NULL,
+    requested_by CHAR(36) NOT NULL,
+    reviewed_by CHAR(36) NULL,
+    review_note TEXT NULL,
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    expires_at DATETIME(6) NOT NULL,
+    decided_at DATETIME(6) NULL,
+    INDEX idx_setting_change_requests_status (status, expires_at)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='Changes to sensitive settings waiting for, or decided by, a second administrator';
+
+CREATE TABLE IF NOT EXISTS setting_change_events (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    request_id CHAR(36) NOT NULL,
+    action VARCHAR(20) NOT NULL,
+    actor_id CHAR(36) NULL COMMENT 'NULL when the system acted, e.g. expiring a request',
+    note TEXT NULL,
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    INDEX idx_setting_change_events_request (request_id, created_at),
+    CONSTRAINT fk_setting_change_events_request FOREIGN KEY (request_id)
+        REFERENCES setting_change_requests(id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='Audit trail of setting change requests';
+
+-- Approval mode starts switched off
+INSERT INTO options (option_name, option_value, option_group, autoload, is_system, value_type, display_name, description)
+SELECT
+    'settings_approval',
+    '{"enabled": false, "sensitive_groups": ["security", "auth", "billing"], "sensitive_keys": [], "expiry_hours": 72}',
+    'security',
+    FALSE,
+    TRUE,
+    'json',
+    'Settings Approval',
+    'Require a second administrator to approve changes to sensitive settings'
+FROM DUAL
+WHERE NOT EXISTS (
+    SELECT 1 FROM options WHERE option_name = 'settings_approval' AND site_id IS NULL
+);
@@ -0,0 +1,56 @@
+-- ============================================
+-- Migration: 00064_webhooks.sql (MySQL / MariaDB)
+-- Description: Outbound webhooks: registered endpoints, the deliveries of
+--              events to them and every attempt at each delivery
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS webhook_endpoints (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    owner_id CHAR(36) NOT NULL,
+    site_id CHAR(36) NULL COMMENT 'Network site whose events are sent; NULL for every site',
+    url TEXT NOT NULL,
+    description TEXT NULL,
+    event_types JSON NOT NULL COMMENT 'Event types or patterns (post.*, *) that are sent',
+    secret VARCHAR(255) NOT NULL COMMENT 'Key of the HMAC-SHA256 signature sent with every delivery',
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6) ON UPDATE CURRENT_TIMESTAMP(6),
+    INDEX idx_webhook_endpoints_owner (owner_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='URLs that events are posted to';
+
+CREATE TABLE IF NOT EXISTS webhook_deliveries (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    endpoint_id CHAR(36) NOT NULL,
+    event_id CHAR(36) NULL,
+    event_type VARCHAR(100) NOT NULL,
+    payload JSON NOT NULL COMMENT 'Request body, sent unchanged on every attempt and redelivery',
+    status VARCHAR(20) NOT NULL DEFAULT 'pending' COMMENT 'pending, succeeded or failed',
+    attempts INT NOT NULL DEFAULT 0,
+    response_status INT NULL,
+    last_error TEXT NULL,
+    redelivery_of CHAR(36) NULL COMMENT 'Delivery this one sends again, when redelivered by hand',
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    delivered_at DATETIME(6) NULL,
+    INDEX idx_webhook_deliveries_endpoint (endpoint_id, created_at),
+    CONSTRAINT fk_webhook_deliveries_endpoint FOREIGN KEY (endpoint_id)
+        REFERENCES webhook_endpoints(id) ON DELETE CASCADE,
+    CONSTRAINT fk_webhook_deliveries_redelivery FOREIGN KEY (redelivery_of)
+        REFERENCES webhook_deliveries(id) ON DELETE SET NULL
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='Events posted, or to be posted, to webhook endpoints';
+
+CREATE TABLE IF NOT EXISTS webhook_delivery_attempts (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    delivery_id CHAR(36) NOT NULL,
+    attempt INT NOT NULL,
+    response_status INT NULL,
+    response_body TEXT NULL COMMENT 'Start of the response body',
+    error TEXT NULL,
+    duration_ms INT NOT NULL DEFAULT 0,
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    INDEX idx_webhook_delivery_attempts_delivery (delivery_id, attempt),
+    CONSTRAINT fk_webhook_delivery_attempts_delivery FOREIGN KEY (delivery_id)
+        REFERENCES webhook_deliveries(id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='Log of every request made for a webhook delivery';
@@ -0,0 +1,41 @@
+-- ============================================
+-- Migration: 00065_search_indexing.sql (MySQL / MariaDB)
+-- Description: Search engine indexing notifications: the URLs search
+--              engines were told about and the requests made to them
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS indexing_urls (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    post_id CHAR(36) NULL,
+    url VARCHAR(2048) NOT NULL,
+    pending_action VARCHAR(10) NULL COMMENT 'updated or deleted, waiting to be submitted',
+    queued_at DATETIME(6) NULL,
+    attempts INT NOT NULL DEFAULT 0,
+    last_action VARCHAR(10) NULL COMMENT 'Action last taken from the queue',
+    last_submitted_at DATETIME(6) NULL,
+    indexnow_status VARCHAR(20) NULL COMMENT 'submitted or failed; NULL when IndexNow was not used',
+    indexnow_response INT NULL,
+    google_status VARCHAR(20) NULL COMMENT 'submitted or failed; NULL when the Google Indexing API was not used',
+    google_response INT NULL,
+    last_error TEXT NULL,
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6) ON UPDATE CURRENT_TIMESTAMP(6),
+    UNIQUE KEY uq_indexing_urls_url (url(768)),
+    INDEX idx_indexing_urls_post (post_id),
+    INDEX idx_indexing_urls_pending (pending_action, queued_at)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='Site URLs and what search engines were last told about them';
+
+CREATE TABLE IF NOT EXISTS indexing_submissions (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    engine VARCHAR(20) NOT NULL,
+    action VARCHAR(10) NOT NULL,
+    urls JSON NOT NULL,
+    response_status INT NULL,
+    response_body TEXT NULL COMMENT 'Start of the response body',
+    error TEXT NULL,
+    duration_ms INT NOT NULL DEFAULT 0,
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    INDEX idx_indexing_submissions_created (created_at)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='Log of requests made to IndexNow and the Google Indexing API';
@@ -0,0 +1,40 @@
+-- ============================================
+-- Migration: 00066_web_vitals.sql (MySQL / MariaDB)
+-- Description: Core Web Vitals field data reported by visitors' browsers,
+--              rolled up per day, and the performance budget alerts
+--              raised from them
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS web_vitals_daily (
+    day DATE NOT NULL,
+    path VARCHAR(512) NOT NULL,
+    template VARCHAR(20) NOT NULL COMMENT 'Kind of page the path renders: home, post, page, event, archive, search or other',
+    device VARCHAR(10) NOT NULL COMMENT 'mobile, tablet or desktop, from the User-Agent',
+    metric VARCHAR(4) NOT NULL COMMENT 'LCP, CLS or INP',
+    samples BIGINT NOT NULL DEFAULT 0,
+    good BIGINT NOT NULL DEFAULT 0,
+    needs_improvement BIGINT NOT NULL DEFAULT 0,
+    poor BIGINT NOT NULL DEFAULT 0,
+    histogram JSON NOT NULL COMMENT 'Sample counts in logarithmic buckets, for percentiles',
+    PRIMARY KEY (day, path, device, metric),
+    INDEX idx_web_vitals_daily_template (template, day)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='Core Web Vitals samples per day, page, device and metric';
+
+CREATE TABLE IF NOT EXISTS web_vitals_alerts (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    template VARCHAR(20) NOT NULL,
+    device VARCHAR(10) NULL COMMENT 'Device the budget applies to; NULL for all devices',
+    metric VARCHAR(4) NOT NULL,
+    budget DOUBLE NOT NULL COMMENT 'Highest p75 the budget allows',
+    p75 DOUBLE NOT NULL COMMENT 'p75 when last checked',
+    baseline_p75 DOUBLE NULL COMMENT 'p75 of the window before',
+    samples BIGINT NOT NULL DEFAULT 0,
+    reason VARCHAR(20) NOT NULL COMMENT 'over_budget or regressed',
+    opened_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    checked_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    resolved_at DATETIME(6) NULL,
+    INDEX idx_web_vitals_alerts_open (template, metric, resolved_at),
+    INDEX idx_web_vitals_alerts_opened (opened_at)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='Templates whose p75 went over their performance budget';
@@ -0,0 +1,52 @@
+-- ============================================
+-- Migration: 00067_inbound_email.sql (MySQL / MariaDB)
+-- Description: Inbound email: mailboxes whose messages become draft posts,
+--              polled over IMAP or posted by Mailgun and Amazon SES, and
+--              the log of every message received
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS inbound_mailboxes (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    site_id CHAR(36) NULL COMMENT 'Network site the posts are created on; NULL for the main site',
+    name VARCHAR(255) NOT NULL,
+    kind VARCHAR(20) NOT NULL COMMENT 'imap, mailgun or ses',
+    token VARCHAR(255) NOT NULL COMMENT 'Secret part of the webhook URL of Mailgun and SES mailboxes',
+    imap_host VARCHAR(255) NULL,
+    imap_port INT NOT NULL DEFAULT 993,
+    imap_tls BOOLEAN NOT NULL DEFAULT TRUE,
+    imap_username VARCHAR(255) NULL,
+    imap_password TEXT NULL,
+    imap_folder VARCHAR(255) NOT NULL DEFAULT 'INBOX',
+    signing_key TEXT NULL COMMENT 'Mailgun webhook signing key',
+    ses_topic_arn VARCHAR(255) NULL COMMENT 'SNS topic SES notifications must come from',
+    allowed_senders JSON NOT NULL COMMENT 'Addresses or @domains that may post; empty for any user who can create posts',
+    category_ids JSON NOT NULL COMMENT 'Categories of the posts created',
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    last_polled_at DATETIME(6) NULL,
+    last_error TEXT NULL,
+    created_by CHAR(36) NOT NULL,
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6) ON UPDATE CURRENT_TIMESTAMP(6),
+    UNIQUE KEY uq_inbound_mailboxes_token (token),
+    INDEX idx_inbound_mailboxes_site (site_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='Mailboxes whose messages are turned into draft posts';
+
+CREATE TABLE IF NOT EXISTS inbound_messages (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    mailbox_id CHAR(36) NOT NULL,
+    message_id VARCHAR(500) NOT NULL COMMENT 'Message-ID header, or a hash of the message without one; received once',
+    sender VARCHAR(255) NULL,
+    subject TEXT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'processing' COMMENT 'processing, created, rejected or failed',
+    post_id CHAR(36) NULL COMMENT 'Draft post created from the message',
+    author_id CHAR(36) NULL,
+    attachments INT NOT NULL DEFAULT 0,
+    error TEXT NULL,
+    received_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    UNIQUE KEY uq_inbound_messages_message (mailbox_id, message_id),
+    INDEX idx_inbound_messages_mailbox (mailbox_id, received_at),
+    CONSTRAINT fk_inbound_messages_mailbox FOREIGN KEY (mailbox_id)
+        REFERENCES inbound_mailboxes(id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='Messages received by inbound mailboxes';
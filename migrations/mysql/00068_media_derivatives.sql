@@ -0,0 +1,20 @@
+-- ============================================
+-- Migration: 00068_media_derivatives.sql (MySQL / MariaDB)
+-- Description: Resized versions of media library images, generated on
+--              first request and offered to browsers through srcset
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS media_derivatives (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    media_id CHAR(36) NOT NULL,
+    size_name VARCHAR(100) NOT NULL COMMENT 'Registered image size the version was made for',
+    storage_path TEXT NOT NULL COMMENT 'Path of the WebP file in storage',
+    width INT NOT NULL,
+    height INT NOT NULL,
+    file_size BIGINT NOT NULL,
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    UNIQUE KEY uq_media_derivatives_size (media_id, size_name),
+    CONSTRAINT fk_media_derivatives_media FOREIGN KEY (media_id)
+        REFERENCES media(id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='Resized versions of media library images';
@@ -0,0 +1,25 @@
+-- ============================================
+-- Migration: 00069_device_authorizations.sql (MySQL / MariaDB)
+-- Description: OAuth2 device authorization grants for CLI login, shared
+--              by every instance so a poll may reach any of them
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS oauth_device_authorizations (
+    id CHAR(36) PRIMARY KEY,
+    device_code_hash VARCHAR(64) NOT NULL COMMENT 'SHA-256 of the device code the client polls with',
+    user_code VARCHAR(16) NOT NULL,
+    client_id VARCHAR(100) NOT NULL,
+    scopes JSON NOT NULL DEFAULT (JSON_ARRAY()),
+    status VARCHAR(20) NOT NULL DEFAULT 'pending' COMMENT 'pending, approved, denied or consumed',
+    user_id CHAR(36) NULL,
+    interval_secs BIGINT NOT NULL,
+    last_polled_at DATETIME(6) NULL,
+    expires_at DATETIME(6) NOT NULL,
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    UNIQUE KEY uq_oauth_device_authorizations_code (device_code_hash),
+    KEY idx_oauth_device_authorizations_user_code (user_code),
+    KEY idx_oauth_device_authorizations_expires (expires_at),
+    CONSTRAINT fk_oauth_device_authorizations_user FOREIGN KEY (user_id)
+        REFERENCES users(id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='OAuth2 device authorization grants (RFC 8628)';
@@ -0,0 +1,37 @@
+-- ============================================
+-- Migration: 00070_site_members.sql (MySQL / MariaDB)
+-- Description: Users who may act on a site of the network, and site_id
+--              on posts, media, menus and search documents so each site
+--              keeps its own
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS site_users (
+    site_id CHAR(36) NOT NULL,
+    user_id CHAR(36) NOT NULL,
+    added_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    PRIMARY KEY (site_id, user_id),
+    KEY idx_site_users_user (user_id),
+    CONSTRAINT fk_site_users_site FOREIGN KEY (site_id) REFERENCES sites(id) ON DELETE CASCADE,
+    CONSTRAINT fk_site_users_user FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='Users who may act on a site besides its owner; network admins act on every site';
+
+ALTER TABLE posts
+    ADD COLUMN site_id CHAR(36) NULL,
+    ADD INDEX idx_posts_site (site_id);
+
+ALTER TABLE media
+    ADD COLUMN site_id CHAR(36) NULL,
+    ADD INDEX idx_media_site (site_id);
+
+-- Sites pick menu slugs of their own
+ALTER TABLE menus
+    ADD COLUMN site_id CHAR(36) NULL COMMENT 'Site the menu belongs to; NULL for the main site',
+    ADD COLUMN site_key CHAR(36) AS (COALESCE(site_id, '')) STORED,
+    DROP INDEX slug,
+    ADD UNIQUE KEY menus_site_slug_unique (site_key, slug);
+
+-- Searches only match documents of their own site
+ALTER TABLE search_documents
+    ADD COLUMN site_id CHAR(36) NULL,
+    ADD INDEX idx_search_documents_site (site_id);
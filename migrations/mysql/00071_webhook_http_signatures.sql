@@ -0,0 +1,9 @@
+-- ============================================
+-- Migration: 00071_webhook_http_signatures.sql (MySQL / MariaDB)
+-- Description: Webhook endpoints that also want deliveries signed with the
+--              site's outbound HTTP message signature key
+-- ============================================
+
+ALTER TABLE webhook_endpoints
+    ADD COLUMN http_signature BOOLEAN NOT NULL DEFAULT FALSE
+        COMMENT 'Whether deliveries carry an RFC 9421 signature by the newest outbound key';
@@ -0,0 +1,22 @@
+-- ============================================
+-- Migration: 00072_saml_sso.sql (MySQL / MariaDB)
+-- Description: Pending SAML login requests and consumed assertions, shared
+--              by every instance so the IdP may post back to any of them
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS saml_requests (
+    id VARCHAR(64) PRIMARY KEY,
+    provider VARCHAR(100) NOT NULL,
+    redirect_after TEXT NULL,
+    expires_at DATETIME(6) NOT NULL,
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    KEY idx_saml_requests_expires (expires_at)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='SAML AuthnRequests awaiting a response from the identity provider';
+
+CREATE TABLE IF NOT EXISTS saml_assertions (
+    id VARCHAR(255) PRIMARY KEY,
+    expires_at DATETIME(6) NOT NULL,
+    KEY idx_saml_assertions_expires (expires_at)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
+  COMMENT='IDs of accepted SAML assertions, kept until they expire so none is replayed';
@@ -4,6 +4,9 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::models::{AnalyticsOverview, AnnotatedReport, DataQuality, RealtimeReport};
+use crate::services::snapshot::OverviewSnapshot;
+
 // Re-export handler functions
 // In a real implementation, these would be proper Axum handlers
 // For now, we define the handler function signatures
@@ -34,6 +37,10 @@ pub struct ResponseMeta {
     pub cached: bool,
     pub request_id: String,
     pub timestamp: chrono::DateTime<chrono::Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data_quality: Option<DataQuality>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
 }
 
 impl<T> ApiResponse<T> {
@@ -46,10 +53,22 @@ impl<T> ApiResponse<T> {
                 cached: false,
                 request_id: uuid::Uuid::new_v4().to_string(),
                 timestamp: chrono::Utc::now(),
+                data_quality: None,
+                warnings: Vec::new(),
             }),
         }
     }
 
+    /// Successful response carrying report data and its quality flags
+    pub fn report(report: AnnotatedReport<T>) -> Self {
+        let mut response = Self::success(report.data);
+        if let Some(meta) = response.meta.as_mut() {
+            meta.warnings = report.data_quality.warnings();
+            meta.data_quality = Some(report.data_quality);
+        }
+        response
+    }
+
     pub fn error(message: &str) -> Self {
         Self {
             success: false,
@@ -59,13 +78,50 @@ impl<T> ApiResponse<T> {
                 cached: false,
                 request_id: uuid::Uuid::new_v4().to_string(),
                 timestamp: chrono::Utc::now(),
+                data_quality: None,
+                warnings: Vec::new(),
             }),
         }
     }
 }
 
+impl ApiResponse<AnalyticsOverview> {
+    /// Successful overview response served from the precomputed snapshot
+    pub fn snapshot(snapshot: OverviewSnapshot, is_stale: bool) -> Self {
+        let mut response = Self::report(AnnotatedReport {
+            data: snapshot.overview,
+            data_quality: snapshot.data_quality,
+        });
+        if let Some(meta) = response.meta.as_mut() {
+            meta.cached = true;
+            if is_stale {
+                meta.warnings.push(format!(
+                    "Showing the overview built at {}; a refresh is under way",
+                    snapshot.built_at.format("%Y-%m-%d %H:%M UTC")
+                ));
+            }
+        }
+        response
+    }
+}
+
+impl ApiResponse<RealtimeReport> {
+    /// Successful realtime response, warning when it is first-party data
+    pub fn realtime(report: RealtimeReport) -> Self {
+        let warning = report.is_fallback().then(|| {
+            "Google Analytics is unavailable; showing first-party realtime data".to_string()
+        });
+        let mut response = Self::success(report);
+        if let (Some(meta), Some(warning)) = (response.meta.as_mut(), warning) {
+            meta.warnings.push(warning);
+        }
+        response
+    }
+}
+
 // Overview handlers
 pub async fn overview_handler() { /* Implementation */ }
+pub async fn refresh_overview_handler() { /* Implementation */ }
 pub async fn realtime_handler() { /* Implementation */ }
 pub async fn realtime_active_users_handler() { /* Implementation */ }
 
@@ -92,8 +148,10 @@ pub async fn site_content_handler() { /* Implementation */ }
 pub async fn landing_pages_handler() { /* Implementation */ }
 pub async fn exit_pages_handler() { /* Implementation */ }
 pub async fn site_speed_handler() { /* Implementation */ }
+pub async fn web_vitals_handler() { /* Implementation */ }
 pub async fn site_search_handler() { /* Implementation */ }
 pub async fn events_handler() { /* Implementation */ }
+pub async fn podcast_downloads_handler() { /* Implementation */ }
 
 // Conversions handlers
 pub async fn goals_handler() { /* Implementation */ }
@@ -117,6 +175,10 @@ pub async fn update_report_handler() { /* Implementation */ }
 pub async fn delete_report_handler() { /* Implementation */ }
 pub async fn run_report_handler() { /* Implementation */ }
 pub async fn export_report_handler() { /* Implementation */ }
+pub async fn schedule_report_handler() { /* Implementation */ }
+pub async fn list_scheduled_reports_handler() { /* Implementation */ }
+pub async fn delivery_status_handler() { /* Implementation */ }
+pub async fn set_delivery_credentials_handler() { /* Implementation */ }
 
 // Settings handlers
 pub async fn get_settings_handler() { /* Implementation */ }
@@ -4,7 +4,8 @@
 //! - Real-time analytics dashboard
 //! - Audience insights and demographics
 //! - Acquisition and traffic analysis
-//! - Behavior and content analytics
+//! - Behavior and content analytics, podcast downloads included
+//! - Core Web Vitals field data and performance budgets in site speed
 //! - Conversion and goal tracking
 //! - E-commerce analytics
 //! - Custom reports and scheduled reporting
@@ -15,7 +16,7 @@
 //! - Real-time visitor tracking and monitoring
 //! - Enterprise dashboard with comprehensive metrics
 //! - Custom report builder
-//! - Scheduled email reports
+//! - Scheduled reports by email, Google Sheets, SFTP and S3
 //! - Data caching for performance
 //! - Privacy-compliant tracking (GDPR, CCPA)
 
@@ -25,14 +26,29 @@ pub mod handlers;
 pub mod admin;
 
 use std::sync::Arc;
+use std::time::Duration;
 
 use async_trait::async_trait;
 use parking_lot::RwLock;
+use rustpress_core::fault::FaultInjector;
+use rustpress_core::http::HttpClient;
+use rustpress_core::plugin_settings::PluginSettings;
 use serde::{Deserialize, Serialize};
 use tracing::{info, warn};
 
-use crate::models::{AnalyticsSettings, ConnectionStatus};
-use crate::services::client::GoogleAnalyticsClient;
+use crate::models::{
+    AnalyticsSettings, ConnectionStatus, DateRange, PodcastDownloadsOverview, WebVitalsOverview,
+};
+use crate::models::RealtimePageHit;
+use crate::services::client::{ClientError, GoogleAnalyticsClient, MemoryTokenStore, TokenStore};
+use crate::services::analytics::SamplingPolicy;
+use crate::services::coordinator::RequestLimits;
+use crate::services::demo::DEMO_PROPERTY_ID;
+use crate::services::privacy::PrivacyPolicy;
+use crate::services::{
+    AnalyticsService, CacheService, FirstPartyCollector, MemorySecretStore, OverviewSnapshotService,
+    PodcastDownloadSource, RealtimeService, ReportDelivery, SecretStore, WebVitalsSource,
+};
 
 /// Plugin version
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -43,6 +59,9 @@ pub const PLUGIN_ID: &str = "rustanalytics";
 /// Plugin name
 pub const PLUGIN_NAME: &str = "RustAnalytics - Google Analytics Integration";
 
+/// How often the Google Analytics client's access token is checked
+const TOKEN_MAINTENANCE_INTERVAL: Duration = Duration::from_secs(60);
+
 /// Plugin state
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum PluginState {
@@ -87,10 +106,30 @@ pub struct RustAnalyticsPlugin {
     state: RwLock<PluginState>,
     /// Plugin settings
     settings: RwLock<AnalyticsSettings>,
+    /// Where settings are saved
+    settings_store: RwLock<Arc<PluginSettings>>,
+    /// Version of the saved settings in use; 0 before the first save
+    settings_version: RwLock<i64>,
     /// Google Analytics API client
     ga_client: RwLock<Option<Arc<GoogleAnalyticsClient>>>,
     /// Connection status
     connection_status: RwLock<ConnectionStatus>,
+    /// Faults handed to the Google Analytics client
+    faults: RwLock<FaultInjector>,
+    /// Outbound HTTP client the Google Analytics client sends through
+    http: RwLock<HttpClient>,
+    /// Where the Google Analytics client persists its access tokens
+    token_store: RwLock<Arc<dyn TokenStore>>,
+    /// Where scheduled report delivery targets keep their credentials
+    secret_store: RwLock<Arc<dyn SecretStore>>,
+    /// Page hits recorded by RustPress itself
+    first_party: Arc<FirstPartyCollector>,
+    /// Precomputed admin overview, built once the client is connected
+    overview_snapshot: RwLock<Option<Arc<OverviewSnapshotService>>>,
+    /// Podcast download statistics kept by RustPress
+    podcast_downloads: RwLock<Option<Arc<dyn PodcastDownloadSource>>>,
+    /// Core Web Vitals field data collected by RustPress
+    web_vitals: RwLock<Option<Arc<dyn WebVitalsSource>>>,
 }
 
 impl RustAnalyticsPlugin {
@@ -123,6 +162,8 @@ impl RustAnalyticsPlugin {
             info,
             state: RwLock::new(PluginState::Inactive),
             settings: RwLock::new(AnalyticsSettings::default()),
+            settings_store: RwLock::new(Arc::new(PluginSettings::in_memory())),
+            settings_version: RwLock::new(0),
             ga_client: RwLock::new(None),
             connection_status: RwLock::new(ConnectionStatus {
                 connected: false,
@@ -131,7 +172,16 @@ impl RustAnalyticsPlugin {
                 account_name: None,
                 last_sync: None,
                 error: None,
+                token: None,
             }),
+            faults: RwLock::new(FaultInjector::disabled()),
+            http: RwLock::new(HttpClient::default()),
+            token_store: RwLock::new(Arc::new(MemoryTokenStore::new())),
+            secret_store: RwLock::new(Arc::new(MemorySecretStore::new())),
+            first_party: Arc::new(FirstPartyCollector::new()),
+            overview_snapshot: RwLock::new(None),
+            podcast_downloads: RwLock::new(None),
+            web_vitals: RwLock::new(None),
         }
     }
 
@@ -140,14 +190,86 @@ impl RustAnalyticsPlugin {
         self.settings.read().clone()
     }
 
-    /// Update settings
-    pub fn update_settings(&self, settings: AnalyticsSettings) {
+    /// Validate settings against the configuration schema, save them over
+    /// the version in use and use them
+    pub async fn update_settings(&self, settings: AnalyticsSettings) -> Result<(), String> {
+        let values = serde_json::to_value(&settings).map_err(|e| e.to_string())?;
+        let store = self.settings_store.read().clone();
+        let version = *self.settings_version.read();
+
+        let saved = store
+            .save(PLUGIN_ID, None, values, self.config_schema().as_ref(), version)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        *self.settings_version.write() = saved.version;
+        *self.settings.write() = settings;
+        Ok(())
+    }
+
+    /// Keep settings in the host's `store`; call
+    /// [`load_settings`](Self::load_settings) on activation to use them
+    pub fn set_settings_store(&self, store: Arc<PluginSettings>) {
+        *self.settings_store.write() = store;
+    }
+
+    /// Use the saved settings, with defaults for anything never saved
+    pub async fn load_settings(&self) -> Result<(), String> {
+        let store = self.settings_store.read().clone();
+        let stored = store
+            .load(PLUGIN_ID, None, self.config_schema().as_ref())
+            .await
+            .map_err(|e| e.to_string())?;
+        self.apply_settings(stored.version, stored.values)
+    }
+
+    /// Follow settings saved through the store by others, such as the
+    /// admin API
+    pub fn watch_settings(self: &Arc<Self>) -> tokio::task::JoinHandle<()> {
+        let mut changes = self.settings_store.read().subscribe();
+        let plugin = Arc::downgrade(self);
+
+        tokio::spawn(async move {
+            loop {
+                let change = match changes.recv().await {
+                    Ok(change) => change,
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                };
+                let Some(plugin) = plugin.upgrade() else { break };
+                if change.plugin_id != PLUGIN_ID
+                    || change.tenant_id.is_some()
+                    || change.version <= *plugin.settings_version.read()
+                {
+                    continue;
+                }
+                if let Err(e) = plugin.apply_settings(change.version, change.values) {
+                    warn!("RustAnalytics: Ignoring saved settings: {}", e);
+                }
+            }
+        })
+    }
+
+    /// Overlay saved values on the defaults and use them
+    fn apply_settings(&self, version: i64, values: serde_json::Value) -> Result<(), String> {
+        let mut merged = serde_json::to_value(AnalyticsSettings::default()).map_err(|e| e.to_string())?;
+        if let (Some(merged), serde_json::Value::Object(values)) = (merged.as_object_mut(), values) {
+            merged.extend(values);
+        }
+        let settings: AnalyticsSettings = serde_json::from_value(merged).map_err(|e| e.to_string())?;
+
         *self.settings.write() = settings;
+        *self.settings_version.write() = version;
+        Ok(())
     }
 
-    /// Get connection status
+    /// Get connection status, with the health of the client's access token
     pub fn connection_status(&self) -> ConnectionStatus {
-        self.connection_status.read().clone()
+        let mut status = self.connection_status.read().clone();
+        if let Some(client) = self.ga_client() {
+            status.token = Some(client.token_health());
+        }
+        status
     }
 
     /// Get the Google Analytics client
@@ -155,20 +277,169 @@ impl RustAnalyticsPlugin {
         self.ga_client.read().clone()
     }
 
+    /// Get the first-party page hit collector
+    pub fn first_party_collector(&self) -> Arc<FirstPartyCollector> {
+        self.first_party.clone()
+    }
+
+    /// Record a page hit when first-party collection is enabled
+    pub fn record_page_hit(&self, hit: RealtimePageHit) {
+        if self.settings.read().first_party_collection {
+            self.first_party.record(hit);
+        }
+    }
+
+    /// Realtime service for the dashboard, falling back to first-party data
+    /// when collection is enabled
+    pub fn realtime_service(&self) -> Option<RealtimeService> {
+        let service = RealtimeService::new(self.ga_client()?);
+        if self.settings.read().first_party_collection {
+            Some(service.with_fallback(self.first_party.clone()))
+        } else {
+            Some(service)
+        }
+    }
+
+    /// Precomputed overview for the admin dashboard
+    pub fn overview_snapshot(&self) -> Option<Arc<OverviewSnapshotService>> {
+        self.overview_snapshot.read().clone()
+    }
+
+    /// Include podcast downloads from the host in behavior reports
+    pub fn set_podcast_download_source(&self, source: Arc<dyn PodcastDownloadSource>) {
+        *self.podcast_downloads.write() = Some(source);
+    }
+
+    /// Podcast downloads over a date range, when the host provides them
+    pub async fn podcast_downloads(
+        &self,
+        date_range: &DateRange,
+    ) -> Option<Result<PodcastDownloadsOverview, ClientError>> {
+        let source = self.podcast_downloads.read().clone()?;
+        Some(
+            source
+                .downloads_overview(date_range)
+                .await
+                .map(PodcastDownloadsOverview::with_percentages),
+        )
+    }
+
+    /// Include Core Web Vitals from the host in site speed reports
+    pub fn set_web_vitals_source(&self, source: Arc<dyn WebVitalsSource>) {
+        *self.web_vitals.write() = Some(source);
+    }
+
+    /// Core Web Vitals over a date range, when the host provides them
+    pub async fn web_vitals(
+        &self,
+        date_range: &DateRange,
+    ) -> Option<Result<WebVitalsOverview, ClientError>> {
+        let source = self.web_vitals.read().clone()?;
+        Some(
+            source
+                .web_vitals_overview(date_range)
+                .await
+                .map(WebVitalsOverview::with_percentages),
+        )
+    }
+
+    /// Inject faults into Google Analytics requests from the next client
+    /// initialization on
+    pub fn set_fault_injector(&self, faults: FaultInjector) {
+        *self.faults.write() = faults;
+    }
+
+    /// Send Google Analytics requests through the host's shared outbound
+    /// HTTP client from the next client initialization on
+    pub fn set_http_client(&self, http: HttpClient) {
+        *self.http.write() = http;
+    }
+
+    /// Persist Google Analytics access tokens in the host's `store` from the
+    /// next client initialization on
+    pub fn set_token_store(&self, store: Arc<dyn TokenStore>) {
+        *self.token_store.write() = store;
+    }
+
+    /// Keep the credentials of scheduled report delivery targets in the
+    /// host's secret `store`
+    pub fn set_secret_store(&self, store: Arc<dyn SecretStore>) {
+        *self.secret_store.write() = store;
+    }
+
+    /// Delivery of scheduled report results to Google Sheets, SFTP and S3
+    /// targets, through the shared HTTP client and secret store
+    pub fn report_delivery(&self) -> ReportDelivery {
+        ReportDelivery::new(self.http.read().clone(), self.secret_store.read().clone())
+    }
+
+    /// Switch the Google Analytics client to a new service account key. The
+    /// previous key stays usable for `key_rotation_grace_minutes`, and the
+    /// new key is kept in the settings for later initializations
+    pub async fn rotate_service_account_key(&self, service_account_json: String) -> Result<(), String> {
+        let client = self.ga_client()
+            .ok_or_else(|| "Google Analytics client is not initialized".to_string())?;
+        let grace = chrono::Duration::minutes(i64::from(self.settings.read().key_rotation_grace_minutes));
+
+        client
+            .rotate_key(&service_account_json, grace)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let mut settings = self.settings();
+        settings.service_account_json = Some(service_account_json);
+        self.update_settings(settings).await?;
+        info!("RustAnalytics: Rotated the service account key");
+        Ok(())
+    }
+
     /// Initialize the Google Analytics client
     pub async fn initialize_client(&self) -> Result<(), String> {
         let settings = self.settings();
 
+        if settings.demo_mode {
+            let client = GoogleAnalyticsClient::demo(settings.demo_seed)
+                .with_privacy(PrivacyPolicy::from_settings(&settings));
+            self.connect(Arc::new(client), &settings);
+            *self.connection_status.write() = ConnectionStatus {
+                connected: true,
+                property_id: Some(DEMO_PROPERTY_ID.to_string()),
+                property_name: Some("Demo property (synthetic data)".to_string()),
+                account_name: None,
+                last_sync: Some(chrono::Utc::now()),
+                error: None,
+                token: None,
+            };
+            info!("RustAnalytics: Serving synthetic demo data");
+            return Ok(());
+        }
+
         if settings.ga_property_id.is_empty() {
             return Err("GA Property ID is not configured".to_string());
         }
 
-        match GoogleAnalyticsClient::new(
+        let http = self.http.read().clone();
+        let token_store = self.token_store.read().clone();
+        match GoogleAnalyticsClient::with_token_store(
+            http,
             settings.ga_property_id.clone(),
             settings.service_account_json.clone(),
+            token_store,
         ).await {
             Ok(client) => {
-                *self.ga_client.write() = Some(Arc::new(client));
+                let client = Arc::new(
+                    client
+                        .with_faults(self.faults.read().clone())
+                        .with_request_limits(RequestLimits {
+                            max_concurrent: settings.max_concurrent_requests as usize,
+                            ..RequestLimits::default()
+                        })
+                        .with_privacy(PrivacyPolicy::from_settings(&settings)),
+                );
+                if settings.service_account_json.is_some() {
+                    client.spawn_token_maintenance(TOKEN_MAINTENANCE_INTERVAL);
+                }
+                self.connect(client, &settings);
                 *self.connection_status.write() = ConnectionStatus {
                     connected: true,
                     property_id: Some(settings.ga_property_id.clone()),
@@ -176,6 +447,7 @@ impl RustAnalyticsPlugin {
                     account_name: None,
                     last_sync: Some(chrono::Utc::now()),
                     error: None,
+                    token: None,
                 };
                 info!("RustAnalytics: Successfully connected to Google Analytics");
                 Ok(())
@@ -189,12 +461,28 @@ impl RustAnalyticsPlugin {
                     account_name: None,
                     last_sync: None,
                     error: Some(e.to_string()),
+                    token: None,
                 };
                 Err(e.to_string())
             }
         }
     }
 
+    /// Serve analytics from `client`
+    fn connect(&self, client: Arc<GoogleAnalyticsClient>, settings: &AnalyticsSettings) {
+        let cache = Arc::new(CacheService::new(
+            Arc::new(()),
+            settings.cache_duration_minutes,
+        ));
+        let analytics = AnalyticsService::new(client.clone(), cache)
+            .with_sampling_policy(SamplingPolicy::from_settings(settings));
+        *self.overview_snapshot.write() = Some(Arc::new(OverviewSnapshotService::new(
+            Arc::new(analytics),
+            settings,
+        )));
+        *self.ga_client.write() = Some(client);
+    }
+
     /// Generate the Google Analytics tracking script
     pub fn generate_tracking_script(&self) -> Option<String> {
         let settings = self.settings();
@@ -203,10 +491,18 @@ impl RustAnalyticsPlugin {
             return None;
         }
 
-        let config_options = if settings.anonymize_ip {
-            "{ 'anonymize_ip': true }"
+        let mut options = Vec::new();
+        if settings.anonymize_ip {
+            options.push("'anonymize_ip': true");
+        }
+        if settings.redact_demographics {
+            // Google signals is where GA gets age, gender and interests from
+            options.push("'allow_google_signals': false");
+        }
+        let config_options = if options.is_empty() {
+            "{}".to_string()
         } else {
-            "{}"
+            format!("{{ {} }}", options.join(", "))
         };
 
         let enhanced_link = if settings.enhanced_link_attribution {
@@ -266,6 +562,18 @@ impl Plugin for RustAnalyticsPlugin {
                     "title": "Measurement ID",
                     "description": "Your GA4 measurement ID (e.g., G-XXXXXXXXXX)"
                 },
+                "demo_mode": {
+                    "type": "boolean",
+                    "title": "Demo Mode",
+                    "description": "Show realistic synthetic data instead of querying Google Analytics, for demos and development without a property or credentials",
+                    "default": false
+                },
+                "demo_seed": {
+                    "type": "integer",
+                    "title": "Demo Data Seed",
+                    "description": "The same seed always produces the same demo data",
+                    "minimum": 0
+                },
                 "enable_tracking": {
                     "type": "boolean",
                     "title": "Enable Tracking",
@@ -290,10 +598,16 @@ impl Plugin for RustAnalyticsPlugin {
                     "description": "Track visits from administrators",
                     "default": false
                 },
+                "first_party_collection": {
+                    "type": "boolean",
+                    "title": "First-party Collection",
+                    "description": "Record page hits in RustPress so the realtime dashboard keeps working when Google Analytics is unavailable",
+                    "default": false
+                },
                 "default_date_range": {
                     "type": "string",
                     "title": "Default Date Range",
-                    "enum": ["today", "yesterday", "last_7_days", "last_30_days", "last_90_days", "this_month", "last_month"],
+                    "enum": ["today", "yesterday", "last_7_days", "last_14_days", "last_28_days", "last_30_days", "last_90_days", "last_365_days", "this_month", "last_month", "this_quarter", "last_quarter", "this_year", "last_year", "custom"],
                     "default": "last_30_days"
                 },
                 "cache_duration_minutes": {
@@ -314,9 +628,37 @@ impl Plugin for RustAnalyticsPlugin {
                     "title": "CCPA Compliant Mode",
                     "description": "Enable CCPA-compliant tracking",
                     "default": true
+                },
+                "redact_city_geo": {
+                    "type": "boolean",
+                    "title": "Hide City-level Location",
+                    "description": "Never request or show cities; location stops at the region",
+                    "default": false
+                },
+                "redact_demographics": {
+                    "type": "boolean",
+                    "title": "Disable Demographics",
+                    "description": "Turn off Google signals and never show age, gender or interests",
+                    "default": false
+                },
+                "redacted_dimensions": {
+                    "type": "array",
+                    "title": "Redacted Dimensions",
+                    "description": "Further GA4 dimensions never requested or shown",
+                    "items": { "type": "string" },
+                    "default": []
+                },
+                "min_users_per_row": {
+                    "type": "integer",
+                    "title": "Minimum Users per Row",
+                    "description": "Hide rows of breakdowns with fewer users than this (0 shows every row)",
+                    "minimum": 0,
+                    "maximum": 1000,
+                    "default": 0
                 }
             },
-            "required": ["ga_property_id"]
+            "if": { "properties": { "demo_mode": { "const": true } }, "required": ["demo_mode"] },
+            "else": { "required": ["ga_property_id"] }
         }))
     }
 }
@@ -350,8 +692,8 @@ mod tests {
         assert_eq!(settings.cache_duration_minutes, 15);
     }
 
-    #[test]
-    fn test_tracking_script_generation() {
+    #[tokio::test]
+    async fn test_tracking_script_generation() {
         let plugin = RustAnalyticsPlugin::new();
 
         // No measurement ID, should return None
@@ -360,10 +702,46 @@ mod tests {
         // With measurement ID
         let mut settings = plugin.settings();
         settings.ga_measurement_id = "G-TEST123".to_string();
-        plugin.update_settings(settings);
+        plugin.update_settings(settings).await.unwrap();
 
         let script = plugin.generate_tracking_script();
         assert!(script.is_some());
         assert!(script.unwrap().contains("G-TEST123"));
     }
+
+    #[tokio::test]
+    async fn test_tracking_script_disables_google_signals() {
+        let plugin = RustAnalyticsPlugin::new();
+        let mut settings = plugin.settings();
+        settings.ga_measurement_id = "G-TEST123".to_string();
+        settings.redact_demographics = true;
+        plugin.update_settings(settings).await.unwrap();
+
+        let script = plugin.generate_tracking_script().unwrap();
+        assert!(script.contains("{ 'anonymize_ip': true, 'allow_google_signals': false }"));
+    }
+
+    #[tokio::test]
+    async fn test_settings_survive_restart() {
+        let store = Arc::new(PluginSettings::in_memory());
+        let plugin = RustAnalyticsPlugin::new();
+        plugin.set_settings_store(store.clone());
+
+        let mut settings = plugin.settings();
+        settings.ga_property_id = "123456789".to_string();
+        settings.cache_duration_minutes = 30;
+        plugin.update_settings(settings.clone()).await.unwrap();
+
+        // Invalid settings are refused and the current ones kept
+        settings.cache_duration_minutes = 0;
+        assert!(plugin.update_settings(settings).await.is_err());
+        assert_eq!(plugin.settings().cache_duration_minutes, 30);
+
+        let restarted = RustAnalyticsPlugin::new();
+        restarted.set_settings_store(store);
+        restarted.load_settings().await.unwrap();
+        assert_eq!(restarted.settings().ga_property_id, "123456789");
+        assert_eq!(restarted.settings().cache_duration_minutes, 30);
+        assert!(restarted.settings().enable_tracking);
+    }
 }
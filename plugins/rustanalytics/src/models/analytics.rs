@@ -3,6 +3,9 @@
 use chrono::{DateTime, Datelike, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 
+use super::api::{RunReportResponse, SamplingMetadata};
+use crate::services::demo::DEMO_REPORT_KIND;
+
 /// Date range for analytics queries
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DateRange {
@@ -47,6 +50,24 @@ impl DateRange {
         let start = NaiveDate::from_ymd_opt(end.year(), end.month(), 1).unwrap();
         Self::new(start, end)
     }
+
+    /// Number of days covered, counting both ends
+    pub fn days(&self) -> i64 {
+        (self.end_date - self.start_date).num_days() + 1
+    }
+
+    /// Split into consecutive ranges of at most `max_days` days
+    pub fn split(&self, max_days: u32) -> Vec<DateRange> {
+        let step = chrono::Duration::days(i64::from(max_days.max(1)));
+        let mut chunks = Vec::new();
+        let mut start = self.start_date;
+        while start <= self.end_date {
+            let end = (start + step - chrono::Duration::days(1)).min(self.end_date);
+            chunks.push(Self::new(start, end));
+            start = end + chrono::Duration::days(1);
+        }
+        chunks
+    }
 }
 
 /// Analytics overview data
@@ -110,6 +131,42 @@ pub struct MetricsComparison {
     pub revenue_change: f64,
 }
 
+impl MetricsComparison {
+    /// Percentage change of each metric from `previous` to `current`
+    pub fn between(current: &OverviewMetrics, previous: &OverviewMetrics) -> Self {
+        Self {
+            sessions_change: percent_change(current.sessions as f64, previous.sessions as f64),
+            users_change: percent_change(current.users as f64, previous.users as f64),
+            new_users_change: percent_change(current.new_users as f64, previous.new_users as f64),
+            pageviews_change: percent_change(current.pageviews as f64, previous.pageviews as f64),
+            pages_per_session_change: percent_change(
+                current.pages_per_session,
+                previous.pages_per_session,
+            ),
+            avg_session_duration_change: percent_change(
+                current.avg_session_duration,
+                previous.avg_session_duration,
+            ),
+            bounce_rate_change: percent_change(current.bounce_rate, previous.bounce_rate),
+            goal_conversion_rate_change: percent_change(
+                current.goal_conversion_rate,
+                previous.goal_conversion_rate,
+            ),
+            revenue_change: percent_change(current.revenue, previous.revenue),
+        }
+    }
+}
+
+/// Change from `previous` to `current` in percent; growth from nothing
+/// counts as 100%
+fn percent_change(current: f64, previous: f64) -> f64 {
+    if previous == 0.0 {
+        if current == 0.0 { 0.0 } else { 100.0 }
+    } else {
+        (current - previous) / previous * 100.0
+    }
+}
+
 /// Daily metrics for chart data
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DailyMetrics {
@@ -307,7 +364,7 @@ pub enum SegmentType {
 }
 
 /// Sampling info from GA
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct SamplingInfo {
     pub is_sampled: bool,
     pub samples_read_counts: Option<i64>,
@@ -315,11 +372,217 @@ pub struct SamplingInfo {
     pub sampling_level: Option<SamplingLevel>,
 }
 
+impl SamplingInfo {
+    /// Sum the sampling metadata of every date range in a response
+    pub fn from_metadata(metadatas: &[SamplingMetadata]) -> Option<Self> {
+        if metadatas.is_empty() {
+            return None;
+        }
+
+        let parse = |value: &Option<String>| value.as_deref().and_then(|v| v.parse::<i64>().ok());
+        let read: i64 = metadatas.iter().filter_map(|m| parse(&m.samples_read_count)).sum();
+        let space: i64 = metadatas.iter().filter_map(|m| parse(&m.sampling_space_size)).sum();
+
+        Some(Self {
+            is_sampled: read < space,
+            samples_read_counts: Some(read),
+            sampling_space_sizes: Some(space),
+            sampling_level: None,
+        })
+    }
+
+    /// Share of the data the report was computed from, between 0 and 1
+    pub fn sample_ratio(&self) -> Option<f64> {
+        match (self.samples_read_counts, self.sampling_space_sizes) {
+            (Some(read), Some(space)) if space > 0 => Some(read as f64 / space as f64),
+            _ => None,
+        }
+    }
+
+    fn combine(&mut self, other: &SamplingInfo) {
+        self.is_sampled |= other.is_sampled;
+        self.samples_read_counts = Some(
+            self.samples_read_counts.unwrap_or(0) + other.samples_read_counts.unwrap_or(0),
+        );
+        self.sampling_space_sizes = Some(
+            self.sampling_space_sizes.unwrap_or(0) + other.sampling_space_sizes.unwrap_or(0),
+        );
+        self.sampling_level = self.sampling_level.or(other.sampling_level);
+    }
+}
+
 /// Sampling level
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+///
+/// GA4 does not take a sampling level; the plugin uses it to decide how
+/// hard to work to avoid sampled data. `Small` accepts sampled responses,
+/// `Default` re-requests a sampled date range in smaller chunks and `Large`
+/// splits long date ranges up front.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum SamplingLevel {
+    #[default]
     Default,
     Small,
     Large,
 }
+
+/// Data-quality flags of a report built from GA responses
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct DataQuality {
+    /// Sampling GA applied, if any
+    pub sampling: Option<SamplingInfo>,
+    /// GA withheld rows to avoid identifying users
+    pub thresholded: bool,
+    /// Rows past GA's cardinality limits were merged into "(other)"
+    pub data_loss_from_other_row: bool,
+    /// Number of GA requests the report was assembled from
+    pub split_requests: u32,
+    /// The data is synthetic, generated in demo mode
+    #[serde(default)]
+    pub synthetic: bool,
+    /// Data the privacy settings withheld from the report
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub redactions: Vec<PrivacyRedaction>,
+}
+
+impl DataQuality {
+    /// Read the quality flags from a GA response
+    pub fn from_response(response: &RunReportResponse) -> Self {
+        let metadata = response.metadata.as_ref();
+        Self {
+            sampling: metadata
+                .and_then(|m| m.sampling_metadatas.as_deref())
+                .and_then(SamplingInfo::from_metadata),
+            thresholded: metadata
+                .and_then(|m| m.subject_to_thresholding)
+                .unwrap_or(false),
+            data_loss_from_other_row: metadata
+                .and_then(|m| m.data_loss_from_other_row)
+                .unwrap_or(false),
+            split_requests: 1,
+            synthetic: response.kind.as_deref() == Some(DEMO_REPORT_KIND),
+            redactions: metadata
+                .and_then(|m| m.privacy_redactions.clone())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Fold in the flags of another response the report was built from
+    pub fn merge(&mut self, other: DataQuality) {
+        match (&mut self.sampling, other.sampling) {
+            (Some(sampling), Some(other)) => sampling.combine(&other),
+            (sampling @ None, other) => *sampling = other,
+            (Some(_), None) => {}
+        }
+        self.thresholded |= other.thresholded;
+        self.data_loss_from_other_row |= other.data_loss_from_other_row;
+        self.split_requests += other.split_requests;
+        self.synthetic |= other.synthetic;
+        for redaction in other.redactions {
+            PrivacyRedaction::record(&mut self.redactions, redaction);
+        }
+    }
+
+    /// Whether GA sampled any of the data
+    pub fn is_sampled(&self) -> bool {
+        self.sampling.as_ref().is_some_and(|s| s.is_sampled)
+    }
+
+    /// Whether the report reflects every event GA recorded
+    pub fn is_exact(&self) -> bool {
+        !self.is_sampled() && !self.thresholded && !self.data_loss_from_other_row
+    }
+
+    /// Human readable warnings for the dashboard
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if self.synthetic {
+            warnings.push("Demo mode: this is generated sample data, not your Google Analytics data".to_string());
+        }
+        if self.is_sampled() {
+            match self.sampling.as_ref().and_then(SamplingInfo::sample_ratio) {
+                Some(ratio) => warnings.push(format!(
+                    "Data is sampled: based on {:.1}% of sessions",
+                    ratio * 100.0
+                )),
+                None => warnings.push("Data is sampled".to_string()),
+            }
+        }
+        if self.thresholded {
+            warnings.push("Some rows were withheld by Google Analytics thresholding".to_string());
+        }
+        if self.data_loss_from_other_row {
+            warnings.push("Rows beyond Google Analytics limits were grouped as \"(other)\"".to_string());
+        }
+        if self.split_requests > 1 {
+            warnings.push(format!(
+                "Totals combine {} requests; users active in more than one period are counted once per period",
+                self.split_requests
+            ));
+        }
+        warnings.extend(
+            self.redactions
+                .iter()
+                .filter(|r| !matches!(r, PrivacyRedaction::MinimumUsers { rows_hidden: 0, .. }))
+                .map(PrivacyRedaction::describe),
+        );
+        warnings
+    }
+}
+
+/// Data withheld from a report by the privacy settings, kept with the
+/// report for compliance audits
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PrivacyRedaction {
+    /// The dimension was never requested from GA; its values read
+    /// "(redacted)"
+    Dimension { dimension: String },
+    /// Rows with fewer than `min_users` users were hidden
+    MinimumUsers { min_users: u32, rows_hidden: u64 },
+}
+
+impl PrivacyRedaction {
+    /// Add `redaction` to `redactions`, combining it with an earlier one of
+    /// the same kind
+    pub fn record(redactions: &mut Vec<PrivacyRedaction>, redaction: PrivacyRedaction) {
+        for existing in redactions.iter_mut() {
+            match (existing, &redaction) {
+                (PrivacyRedaction::Dimension { dimension }, PrivacyRedaction::Dimension { dimension: other })
+                    if dimension == other =>
+                {
+                    return;
+                }
+                (
+                    PrivacyRedaction::MinimumUsers { min_users, rows_hidden },
+                    PrivacyRedaction::MinimumUsers { min_users: other, rows_hidden: hidden },
+                ) if min_users == other => {
+                    *rows_hidden += hidden;
+                    return;
+                }
+                _ => {}
+            }
+        }
+        redactions.push(redaction);
+    }
+
+    /// Human readable description for the dashboard
+    pub fn describe(&self) -> String {
+        match self {
+            PrivacyRedaction::Dimension { dimension } => {
+                format!("{} is withheld by the privacy settings", dimension)
+            }
+            PrivacyRedaction::MinimumUsers { min_users, rows_hidden } => format!(
+                "{} rows with fewer than {} users are hidden by the privacy settings",
+                rows_hidden, min_users
+            ),
+        }
+    }
+}
+
+/// Report data annotated with its data quality
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnnotatedReport<T> {
+    pub data: T,
+    pub data_quality: DataQuality,
+}
@@ -2,6 +2,8 @@
 
 use serde::{Deserialize, Serialize};
 
+use super::analytics::PrivacyRedaction;
+
 /// Google Analytics Data API (GA4) run report request
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -295,6 +297,21 @@ pub struct ResponseMetaData {
     pub schema_restriction_response: Option<SchemaRestrictionResponse>,
     pub currency_code: Option<String>,
     pub time_zone: Option<String>,
+    pub subject_to_thresholding: Option<bool>,
+    pub sampling_metadatas: Option<Vec<SamplingMetadata>>,
+    /// Redactions the plugin's privacy settings applied; never sent by GA
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub privacy_redactions: Option<Vec<PrivacyRedaction>>,
+}
+
+/// Sampling applied to one date range of a report
+///
+/// Counts are int64 values, which the API encodes as strings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SamplingMetadata {
+    pub samples_read_count: Option<String>,
+    pub sampling_space_size: Option<String>,
 }
 
 /// Schema restriction response
@@ -1,6 +1,6 @@
 //! Behavior analytics models
 
-use chrono::NaiveDate;
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 
 use super::DateRange;
@@ -218,6 +218,114 @@ pub struct DomTimingData {
     pub dom_interactive_sample: u64,
 }
 
+/// Core Web Vitals field data, measured by RustPress in visitors' browsers
+/// rather than by Google Analytics
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebVitalsOverview {
+    pub date_range: DateRange,
+    /// LCP, CLS and INP over every page view
+    pub metrics: Vec<WebVitalData>,
+    pub device_breakdown: Vec<DeviceVitalsData>,
+    pub template_breakdown: Vec<TemplateVitalsData>,
+    pub top_pages: Vec<PageVitalsData>,
+    pub vitals_trend: Vec<VitalsTrendData>,
+    /// Performance budgets currently exceeded
+    pub budget_alerts: Vec<VitalsBudgetAlert>,
+}
+
+impl WebVitalsOverview {
+    /// Fill in each metric's share of good, needs-improvement and poor
+    /// samples
+    pub fn with_percentages(mut self) -> Self {
+        let groups = std::iter::once(&mut self.metrics)
+            .chain(self.device_breakdown.iter_mut().map(|d| &mut d.metrics))
+            .chain(self.template_breakdown.iter_mut().map(|t| &mut t.metrics))
+            .chain(self.top_pages.iter_mut().map(|p| &mut p.metrics));
+        for metrics in groups {
+            for metric in metrics.iter_mut() {
+                let rated = metric.good + metric.needs_improvement + metric.poor;
+                let share = |samples: u64| {
+                    if rated == 0 {
+                        0.0
+                    } else {
+                        samples as f64 * 100.0 / rated as f64
+                    }
+                };
+                metric.good_percentage = share(metric.good);
+                metric.needs_improvement_percentage = share(metric.needs_improvement);
+                metric.poor_percentage = share(metric.poor);
+            }
+        }
+        self
+    }
+}
+
+/// One Core Web Vital over a group of page views
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebVitalData {
+    /// LCP, CLS or INP
+    pub metric: String,
+    pub samples: u64,
+    /// 75th percentile, in milliseconds for LCP and INP
+    pub p75: Option<f64>,
+    /// Rating of the p75: good, needs_improvement or poor
+    pub rating: Option<String>,
+    pub good: u64,
+    pub needs_improvement: u64,
+    pub poor: u64,
+    pub good_percentage: f64,
+    pub needs_improvement_percentage: f64,
+    pub poor_percentage: f64,
+}
+
+/// Core Web Vitals on one kind of device
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceVitalsData {
+    pub device: String,
+    pub metrics: Vec<WebVitalData>,
+}
+
+/// Core Web Vitals of one template (home, post, archive, ...)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateVitalsData {
+    pub template: String,
+    pub metrics: Vec<WebVitalData>,
+}
+
+/// Core Web Vitals of one page
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageVitalsData {
+    pub page_path: String,
+    pub template: String,
+    pub metrics: Vec<WebVitalData>,
+}
+
+/// Core Web Vitals trend data
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VitalsTrendData {
+    pub date: NaiveDate,
+    pub lcp_p75: Option<f64>,
+    pub cls_p75: Option<f64>,
+    pub inp_p75: Option<f64>,
+}
+
+/// A performance budget a template went over
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VitalsBudgetAlert {
+    pub template: String,
+    /// Device of the budget; all devices when absent
+    pub device: Option<String>,
+    pub metric: String,
+    /// Highest p75 the budget allows
+    pub budget: f64,
+    pub p75: f64,
+    /// p75 of the window before
+    pub baseline_p75: Option<f64>,
+    /// over_budget or regressed
+    pub reason: String,
+    pub opened_at: DateTime<Utc>,
+}
+
 /// Speed suggestions data
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SpeedSuggestions {
@@ -569,3 +677,65 @@ pub struct ExceptionTrendData {
     pub exceptions: u64,
     pub fatal_exceptions: u64,
 }
+
+/// Podcast episode downloads, counted by RustPress from requests for
+/// episode audio rather than by Google Analytics
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PodcastDownloadsOverview {
+    pub date_range: DateRange,
+    /// Listeners who fetched at least a minute of an episode, per day
+    pub downloads: u64,
+    /// Audio requests, byte ranges and retries included
+    pub requests: u64,
+    pub bytes_served: u64,
+    pub downloads_trend: Vec<DownloadsTrendData>,
+    pub top_episodes: Vec<EpisodeDownloadData>,
+    pub podcast_apps: Vec<PodcastAppData>,
+}
+
+impl PodcastDownloadsOverview {
+    /// Fill in each episode's and app's share of the downloads
+    pub fn with_percentages(mut self) -> Self {
+        let share = |downloads: u64| {
+            if self.downloads == 0 {
+                0.0
+            } else {
+                downloads as f64 * 100.0 / self.downloads as f64
+            }
+        };
+        for episode in &mut self.top_episodes {
+            episode.percentage = share(episode.downloads);
+        }
+        for app in &mut self.podcast_apps {
+            app.percentage = share(app.downloads);
+        }
+        self
+    }
+}
+
+/// Downloads trend data
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownloadsTrendData {
+    pub date: NaiveDate,
+    pub downloads: u64,
+}
+
+/// Downloads of one episode
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EpisodeDownloadData {
+    pub post_id: String,
+    pub episode_title: String,
+    pub page_path: String,
+    pub downloads: u64,
+    pub requests: u64,
+    pub bytes_served: u64,
+    pub percentage: f64,
+}
+
+/// Downloads from one podcast app
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PodcastAppData {
+    pub app: String,
+    pub downloads: u64,
+    pub percentage: f64,
+}
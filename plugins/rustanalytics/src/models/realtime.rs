@@ -25,6 +25,33 @@ pub struct RealtimeOverview {
     pub timestamp: DateTime<Utc>,
 }
 
+/// Where realtime data came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RealtimeSource {
+    /// Google Analytics 4 realtime API
+    Ga4,
+    /// RustPress's own page hit collection
+    FirstParty,
+}
+
+/// Realtime overview tagged with the source it was read from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RealtimeReport {
+    #[serde(flatten)]
+    pub overview: RealtimeOverview,
+    pub source: RealtimeSource,
+    /// Why GA4 was not used, when falling back
+    pub fallback_reason: Option<String>,
+}
+
+impl RealtimeReport {
+    /// Whether the data came from the first-party fallback
+    pub fn is_fallback(&self) -> bool {
+        self.source == RealtimeSource::FirstParty
+    }
+}
+
 /// Pageviews per minute data point
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PageviewsPerMinute {
@@ -4,7 +4,7 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use super::{DateRange, DateRangePreset, ReportFormat, ReportFrequency, SamplingInfo};
+use super::{DateRange, DateRangePreset, PrivacyRedaction, ReportFormat, ReportFrequency, SamplingInfo};
 
 /// Custom report definition
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -40,6 +40,128 @@ pub struct ScheduledReport {
     pub last_run: Option<DateTime<Utc>>,
     pub last_status: Option<ReportStatus>,
     pub created_at: DateTime<Utc>,
+    /// Where results are pushed besides the email recipients
+    #[serde(default)]
+    pub targets: Vec<DeliveryTarget>,
+    /// Outcome of the last run for each target
+    #[serde(default)]
+    pub deliveries: Vec<DeliveryStatus>,
+}
+
+/// Destination a scheduled report's results are pushed to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeliveryTarget {
+    pub id: Uuid,
+    pub name: String,
+    pub destination: DeliveryDestination,
+    pub format: DeliveryFormat,
+    /// Secret store entry holding the target's credentials
+    #[serde(default)]
+    pub credential_id: Option<String>,
+    pub enabled: bool,
+}
+
+/// Delivery destinations
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DeliveryDestination {
+    /// Sheet of a Google spreadsheet, written with OAuth credentials
+    GoogleSheet {
+        spreadsheet_id: String,
+        sheet_name: String,
+        /// Add rows below the existing ones instead of replacing the sheet
+        #[serde(default)]
+        append: bool,
+    },
+    /// Directory on an SFTP server
+    Sftp {
+        host: String,
+        #[serde(default = "default_sftp_port")]
+        port: u16,
+        username: String,
+        directory: String,
+        /// SHA-256 fingerprint of the server's host key, as printed by
+        /// `ssh-keygen -l` (`SHA256:...`)
+        host_key_fingerprint: String,
+    },
+    /// Key prefix in an S3-compatible bucket
+    S3 {
+        /// `https://s3.{region}.amazonaws.com/{bucket}` or
+        /// `https://{bucket}.s3.amazonaws.com`
+        bucket_url: String,
+        region: String,
+        #[serde(default)]
+        prefix: String,
+    },
+}
+
+fn default_sftp_port() -> u16 {
+    22
+}
+
+impl DeliveryDestination {
+    pub fn kind(&self) -> DeliveryKind {
+        match self {
+            Self::GoogleSheet { .. } => DeliveryKind::GoogleSheet,
+            Self::Sftp { .. } => DeliveryKind::Sftp,
+            Self::S3 { .. } => DeliveryKind::S3,
+        }
+    }
+}
+
+/// Kinds of delivery destination
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryKind {
+    GoogleSheet,
+    Sftp,
+    S3,
+}
+
+/// File formats for delivered results
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryFormat {
+    Csv,
+    Parquet,
+}
+
+impl DeliveryFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Parquet => "parquet",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Csv => "text/csv",
+            Self::Parquet => "application/vnd.apache.parquet",
+        }
+    }
+}
+
+/// Outcome of delivering a run's results to one target
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeliveryStatus {
+    pub target_id: Uuid,
+    pub state: DeliveryState,
+    /// Where the results landed
+    pub location: Option<String>,
+    pub size_bytes: u64,
+    pub attempted_at: DateTime<Utc>,
+    pub error: Option<String>,
+}
+
+/// Delivery states
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryState {
+    Delivered,
+    Failed,
+    /// The target is disabled
+    Skipped,
 }
 
 /// Report execution status
@@ -205,6 +327,11 @@ pub struct ReportResult {
     pub totals: Option<ReportRow>,
     pub row_count: u64,
     pub sampling_info: Option<SamplingInfo>,
+    #[serde(default)]
+    pub data_quality_warnings: Vec<String>,
+    /// Data the privacy settings withheld, for compliance audits
+    #[serde(default)]
+    pub redactions: Vec<PrivacyRedaction>,
     pub generated_at: DateTime<Utc>,
 }
 
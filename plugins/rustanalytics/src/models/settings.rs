@@ -3,6 +3,8 @@
 use serde::{Deserialize, Serialize};
 use validator::Validate;
 
+use super::analytics::{DateRange, SamplingLevel};
+
 /// Main plugin settings
 #[derive(Debug, Clone, Serialize, Deserialize, Validate)]
 pub struct AnalyticsSettings {
@@ -12,6 +14,16 @@ pub struct AnalyticsSettings {
     pub ga_measurement_id: String,
     pub ga_api_secret: String,
     pub service_account_json: Option<String>,
+    /// Minutes the previous service account key stays usable after a rotation
+    #[serde(default = "default_key_rotation_grace_minutes")]
+    pub key_rotation_grace_minutes: u32,
+    /// Serve synthetic data instead of querying GA4, for demos and UI work
+    /// without a property or credentials
+    #[serde(default)]
+    pub demo_mode: bool,
+    /// Seed of the synthetic data; the same seed always gives the same numbers
+    #[serde(default = "default_demo_seed")]
+    pub demo_seed: u64,
 
     // Tracking Options
     pub enable_tracking: bool,
@@ -22,6 +34,10 @@ pub struct AnalyticsSettings {
     pub cookie_consent_required: bool,
     pub enhanced_link_attribution: bool,
     pub enhanced_ecommerce: bool,
+    /// Record page hits in RustPress so the realtime dashboard can fall
+    /// back to them when GA4 is unavailable
+    #[serde(default)]
+    pub first_party_collection: bool,
 
     // Data Retention
     #[validate(range(min = 1, max = 50))]
@@ -47,6 +63,19 @@ pub struct AnalyticsSettings {
     pub cross_domain_tracking: Vec<String>,
     pub content_grouping: Vec<ContentGroup>,
 
+    // Sampling
+    #[serde(default)]
+    pub sampling_level: SamplingLevel,
+    #[serde(default = "default_max_days_per_request")]
+    #[validate(range(min = 1, max = 365))]
+    pub max_days_per_request: u32,
+
+    // Request Coordination
+    /// GA requests running at the same time
+    #[serde(default = "default_max_concurrent_requests")]
+    #[validate(range(min = 1, max = 10))]
+    pub max_concurrent_requests: u32,
+
     // Report Settings
     pub report_email_enabled: bool,
     pub report_email_recipients: Vec<String>,
@@ -57,6 +86,19 @@ pub struct AnalyticsSettings {
     pub gdpr_compliant: bool,
     pub ccpa_compliant: bool,
     pub data_processing_location: DataProcessingLocation,
+    /// Never request or show city-level location
+    #[serde(default)]
+    pub redact_city_geo: bool,
+    /// Never collect or show age, gender and interests
+    #[serde(default)]
+    pub redact_demographics: bool,
+    /// Further GA dimensions never requested or shown
+    #[serde(default)]
+    pub redacted_dimensions: Vec<String>,
+    /// Hide rows of breakdowns with fewer users than this; 0 shows every row
+    #[serde(default)]
+    #[validate(range(max = 1000))]
+    pub min_users_per_row: u32,
 }
 
 impl Default for AnalyticsSettings {
@@ -66,6 +108,9 @@ impl Default for AnalyticsSettings {
             ga_measurement_id: String::new(),
             ga_api_secret: String::new(),
             service_account_json: None,
+            key_rotation_grace_minutes: default_key_rotation_grace_minutes(),
+            demo_mode: false,
+            demo_seed: default_demo_seed(),
             enable_tracking: true,
             track_logged_in_users: true,
             track_admin_users: false,
@@ -74,6 +119,7 @@ impl Default for AnalyticsSettings {
             cookie_consent_required: false,
             enhanced_link_attribution: true,
             enhanced_ecommerce: false,
+            first_party_collection: false,
             data_retention_period: 26,
             cache_duration_minutes: 15,
             sync_frequency_hours: 1,
@@ -89,6 +135,9 @@ impl Default for AnalyticsSettings {
             excluded_user_roles: vec!["administrator".to_string()],
             cross_domain_tracking: Vec::new(),
             content_grouping: Vec::new(),
+            sampling_level: SamplingLevel::Default,
+            max_days_per_request: default_max_days_per_request(),
+            max_concurrent_requests: default_max_concurrent_requests(),
             report_email_enabled: false,
             report_email_recipients: Vec::new(),
             report_frequency: ReportFrequency::Weekly,
@@ -96,21 +145,47 @@ impl Default for AnalyticsSettings {
             gdpr_compliant: true,
             ccpa_compliant: true,
             data_processing_location: DataProcessingLocation::Auto,
+            redact_city_geo: false,
+            redact_demographics: false,
+            redacted_dimensions: Vec::new(),
+            min_users_per_row: 0,
         }
     }
 }
 
+fn default_max_days_per_request() -> u32 {
+    90
+}
+
+fn default_key_rotation_grace_minutes() -> u32 {
+    60
+}
+
+fn default_max_concurrent_requests() -> u32 {
+    4
+}
+
+fn default_demo_seed() -> u64 {
+    crate::services::demo::DEFAULT_DEMO_SEED
+}
+
 /// Date range presets for analytics queries
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum DateRangePreset {
     Today,
     Yesterday,
+    #[serde(rename = "last_7_days", alias = "last7_days")]
     Last7Days,
+    #[serde(rename = "last_14_days", alias = "last14_days")]
     Last14Days,
+    #[serde(rename = "last_28_days", alias = "last28_days")]
     Last28Days,
+    #[serde(rename = "last_30_days", alias = "last30_days")]
     Last30Days,
+    #[serde(rename = "last_90_days", alias = "last90_days")]
     Last90Days,
+    #[serde(rename = "last_365_days", alias = "last365_days")]
     Last365Days,
     ThisMonth,
     LastMonth,
@@ -121,6 +196,26 @@ pub enum DateRangePreset {
     Custom,
 }
 
+impl DateRangePreset {
+    /// Date range the preset covers today; custom and quarter or year
+    /// presets fall back to the last 30 days
+    pub fn date_range(&self) -> DateRange {
+        match self {
+            Self::Today => DateRange::today(),
+            Self::Yesterday => DateRange::yesterday(),
+            Self::Last7Days => DateRange::last_n_days(7),
+            Self::Last14Days => DateRange::last_n_days(14),
+            Self::Last28Days => DateRange::last_n_days(28),
+            Self::Last30Days => DateRange::last_n_days(30),
+            Self::Last90Days => DateRange::last_n_days(90),
+            Self::Last365Days => DateRange::last_n_days(365),
+            Self::ThisMonth => DateRange::this_month(),
+            Self::LastMonth => DateRange::last_month(),
+            _ => DateRange::last_n_days(30),
+        }
+    }
+}
+
 /// Custom dimension configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CustomDimension {
@@ -252,6 +347,38 @@ pub struct ConnectionStatus {
     pub account_name: Option<String>,
     pub last_sync: Option<chrono::DateTime<chrono::Utc>>,
     pub error: Option<String>,
+    /// Health of the access token the client authenticates with
+    #[serde(default)]
+    pub token: Option<TokenHealth>,
+}
+
+/// State of the client's access token
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenState {
+    /// No token has been minted yet
+    Missing,
+    Valid,
+    /// Valid, but past its scheduled refresh
+    RefreshDue,
+    Expired,
+}
+
+/// Access token lifecycle, as shown with the connection status
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenHealth {
+    pub state: TokenState,
+    /// `private_key_id` of the key in use
+    pub key_id: Option<String>,
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub refresh_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub last_refresh: Option<chrono::DateTime<chrono::Utc>>,
+    pub last_refresh_error: Option<String>,
+    pub consecutive_failures: u32,
+    /// Key replaced by a rotation, still accepted as a fallback
+    pub previous_key_id: Option<String>,
+    /// End of the previous key's grace window
+    pub previous_key_retires_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
 /// Available GA4 properties for selection
@@ -10,6 +10,38 @@ use tracing::debug;
 use crate::models::*;
 use crate::services::cache::CacheService;
 use crate::services::client::{ClientError, GoogleAnalyticsClient};
+use crate::services::coordinator::RequestPriority;
+
+/// Name of the requested date range in overview reports with a comparison
+const CURRENT_RANGE: &str = "current";
+
+/// Name of the previous period in overview reports with a comparison
+const COMPARISON_RANGE: &str = "comparison";
+
+/// How the service splits date ranges to reduce sampling
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SamplingPolicy {
+    /// How hard to work to avoid sampled data
+    pub level: SamplingLevel,
+    /// Longest date range requested at once when splitting
+    pub max_days_per_request: u32,
+}
+
+impl SamplingPolicy {
+    /// Build the policy from the plugin settings
+    pub fn from_settings(settings: &AnalyticsSettings) -> Self {
+        Self {
+            level: settings.sampling_level,
+            max_days_per_request: settings.max_days_per_request,
+        }
+    }
+}
+
+impl Default for SamplingPolicy {
+    fn default() -> Self {
+        Self::from_settings(&AnalyticsSettings::default())
+    }
+}
 
 /// Analytics Service for fetching and processing GA data
 pub struct AnalyticsService {
@@ -17,12 +49,24 @@ pub struct AnalyticsService {
     client: Arc<GoogleAnalyticsClient>,
     /// Cache service
     cache: Arc<CacheService>,
+    /// Date range splitting policy
+    sampling: SamplingPolicy,
 }
 
 impl AnalyticsService {
     /// Create a new analytics service
     pub fn new(client: Arc<GoogleAnalyticsClient>, cache: Arc<CacheService>) -> Self {
-        Self { client, cache }
+        Self {
+            client,
+            cache,
+            sampling: SamplingPolicy::default(),
+        }
+    }
+
+    /// Use a different date range splitting policy
+    pub fn with_sampling_policy(mut self, sampling: SamplingPolicy) -> Self {
+        self.sampling = sampling;
+        self
     }
 
     /// Get analytics overview
@@ -30,30 +74,51 @@ impl AnalyticsService {
         &self,
         date_range: DateRange,
         compare: bool,
-    ) -> Result<AnalyticsOverview, ClientError> {
+    ) -> Result<AnnotatedReport<AnalyticsOverview>, ClientError> {
         let cache_key = format!(
             "overview:{}:{}:{}",
             date_range.start_date, date_range.end_date, compare
         );
 
         // Check cache
-        if let Some(cached) = self.cache.get::<AnalyticsOverview>(&cache_key).await {
+        if let Some(cached) = self
+            .cache
+            .get::<AnnotatedReport<AnalyticsOverview>>(&cache_key)
+            .await
+        {
             debug!("Returning cached overview data");
             return Ok(cached);
         }
 
+        self.fetch_overview(date_range, compare).await
+    }
+
+    /// Fetch the analytics overview from GA, skipping the cache lookup but
+    /// refreshing the cached copy
+    pub async fn fetch_overview(
+        &self,
+        date_range: DateRange,
+        compare: bool,
+    ) -> Result<AnnotatedReport<AnalyticsOverview>, ClientError> {
+        let cache_key = format!(
+            "overview:{}:{}:{}",
+            date_range.start_date, date_range.end_date, compare
+        );
+
         // Build the report request
         let mut date_ranges = vec![GoogleAnalyticsClient::build_date_range(&date_range)];
 
-        // Add comparison date range if requested
+        // Add comparison date range if requested; named ranges make GA tag
+        // every row with the range it belongs to
         if compare {
+            date_ranges[0].name = Some(CURRENT_RANGE.to_string());
             let days = (date_range.end_date - date_range.start_date).num_days();
             let compare_end = date_range.start_date - chrono::Duration::days(1);
             let compare_start = compare_end - chrono::Duration::days(days);
             date_ranges.push(ApiDateRange {
                 start_date: compare_start.format("%Y-%m-%d").to_string(),
                 end_date: compare_end.format("%Y-%m-%d").to_string(),
-                name: Some("comparison".to_string()),
+                name: Some(COMPARISON_RANGE.to_string()),
             });
         }
 
@@ -91,10 +156,19 @@ impl AnalyticsService {
             return_property_quota: None,
         };
 
-        let response = self.client.run_report(request).await?;
+        // The overview fills the top of the dashboard. Comparison requests
+        // carry two date ranges and are never split
+        let (response, data_quality) = if compare {
+            self.run_report(request, RequestPriority::Critical).await?
+        } else {
+            self.run_split_report(request, &date_range, RequestPriority::Critical).await?
+        };
 
         // Process the response
-        let overview = self.process_overview_response(response, date_range, compare)?;
+        let overview = AnnotatedReport {
+            data: self.process_overview_response(response, date_range, compare)?,
+            data_quality,
+        };
 
         // Cache the result
         self.cache.set(&cache_key, &overview).await;
@@ -107,43 +181,31 @@ impl AnalyticsService {
         &self,
         response: RunReportResponse,
         date_range: DateRange,
-        _compare: bool,
+        compare: bool,
     ) -> Result<AnalyticsOverview, ClientError> {
         let mut chart_data = Vec::new();
         let mut metrics = OverviewMetrics::default();
-
-        // Process totals
-        if let Some(totals) = response.totals.as_ref().and_then(|t| t.first()) {
-            if let Some(values) = &totals.metric_values {
-                metrics = OverviewMetrics {
-                    sessions: Self::parse_metric_value(&values.get(0)),
-                    users: Self::parse_metric_value(&values.get(1)),
-                    new_users: Self::parse_metric_value(&values.get(2)),
-                    pageviews: Self::parse_metric_value(&values.get(3)),
-                    pages_per_session: Self::parse_metric_float(&values.get(4)),
-                    avg_session_duration: Self::parse_metric_float(&values.get(5)),
-                    bounce_rate: Self::parse_metric_float(&values.get(6)) * 100.0,
-                    goal_conversion_rate: 0.0,
-                    goal_completions: Self::parse_metric_value(&values.get(7)),
-                    goal_value: Self::parse_metric_float(&values.get(8)),
-                    transactions: Self::parse_metric_value(&values.get(9)),
-                    revenue: Self::parse_metric_float(&values.get(8)),
-                    ecommerce_conversion_rate: 0.0,
-                };
-
-                // Calculate conversion rates
-                if metrics.sessions > 0 {
-                    metrics.goal_conversion_rate =
-                        (metrics.goal_completions as f64 / metrics.sessions as f64) * 100.0;
-                    metrics.ecommerce_conversion_rate =
-                        (metrics.transactions as f64 / metrics.sessions as f64) * 100.0;
-                }
+        let mut comparison = None;
+
+        // Process totals, one row per date range when comparing
+        for totals in response.totals.iter().flatten() {
+            let Some(values) = &totals.metric_values else {
+                continue;
+            };
+            if compare && Self::in_comparison_range(totals) {
+                comparison = Some(Self::overview_metrics(values));
+            } else {
+                metrics = Self::overview_metrics(values);
             }
         }
+        let comparison = comparison.map(|previous| MetricsComparison::between(&metrics, &previous));
 
         // Process daily data for chart
         if let Some(rows) = response.rows {
             for row in rows {
+                if compare && Self::in_comparison_range(&row) {
+                    continue;
+                }
                 if let (Some(dims), Some(vals)) = (&row.dimension_values, &row.metric_values) {
                     if let Some(date_str) = dims.get(0).and_then(|d| d.value.as_ref()) {
                         if let Ok(date) = NaiveDate::parse_from_str(date_str, "%Y%m%d") {
@@ -167,17 +229,53 @@ impl AnalyticsService {
         Ok(AnalyticsOverview {
             date_range,
             metrics,
-            comparison: None, // TODO: Implement comparison processing
+            comparison,
             chart_data,
         })
     }
 
+    /// Overview metrics from a totals row of the overview report
+    fn overview_metrics(values: &[MetricValue]) -> OverviewMetrics {
+        let mut metrics = OverviewMetrics {
+            sessions: Self::parse_metric_value(&values.get(0)),
+            users: Self::parse_metric_value(&values.get(1)),
+            new_users: Self::parse_metric_value(&values.get(2)),
+            pageviews: Self::parse_metric_value(&values.get(3)),
+            pages_per_session: Self::parse_metric_float(&values.get(4)),
+            avg_session_duration: Self::parse_metric_float(&values.get(5)),
+            bounce_rate: Self::parse_metric_float(&values.get(6)) * 100.0,
+            goal_conversion_rate: 0.0,
+            goal_completions: Self::parse_metric_value(&values.get(7)),
+            goal_value: Self::parse_metric_float(&values.get(8)),
+            transactions: Self::parse_metric_value(&values.get(9)),
+            revenue: Self::parse_metric_float(&values.get(8)),
+            ecommerce_conversion_rate: 0.0,
+        };
+
+        // Calculate conversion rates
+        if metrics.sessions > 0 {
+            metrics.goal_conversion_rate =
+                (metrics.goal_completions as f64 / metrics.sessions as f64) * 100.0;
+            metrics.ecommerce_conversion_rate =
+                (metrics.transactions as f64 / metrics.sessions as f64) * 100.0;
+        }
+
+        metrics
+    }
+
+    /// Whether GA tagged a row of a two-range report with the comparison range
+    fn in_comparison_range(row: &Row) -> bool {
+        row.dimension_values.iter().flatten().any(|value| {
+            value.value.as_deref() == Some(COMPARISON_RANGE)
+        })
+    }
+
     /// Get traffic sources data
     pub async fn get_traffic_sources(
         &self,
         date_range: DateRange,
         limit: Option<i64>,
-    ) -> Result<Vec<TrafficSource>, ClientError> {
+    ) -> Result<AnnotatedReport<Vec<TrafficSource>>, ClientError> {
         let cache_key = format!(
             "traffic_sources:{}:{}:{}",
             date_range.start_date,
@@ -185,7 +283,11 @@ impl AnalyticsService {
             limit.unwrap_or(10)
         );
 
-        if let Some(cached) = self.cache.get::<Vec<TrafficSource>>(&cache_key).await {
+        if let Some(cached) = self
+            .cache
+            .get::<AnnotatedReport<Vec<TrafficSource>>>(&cache_key)
+            .await
+        {
             return Ok(cached);
         }
 
@@ -216,8 +318,11 @@ impl AnalyticsService {
             return_property_quota: None,
         };
 
-        let response = self.client.run_report(request).await?;
-        let sources = self.process_traffic_sources_response(response)?;
+        let (response, data_quality) = self.run_report(request, RequestPriority::Normal).await?;
+        let sources = AnnotatedReport {
+            data: self.process_traffic_sources_response(response)?,
+            data_quality,
+        };
 
         self.cache.set(&cache_key, &sources).await;
         Ok(sources)
@@ -265,10 +370,14 @@ impl AnalyticsService {
     pub async fn get_channels(
         &self,
         date_range: DateRange,
-    ) -> Result<Vec<ChannelData>, ClientError> {
+    ) -> Result<AnnotatedReport<Vec<ChannelData>>, ClientError> {
         let cache_key = format!("channels:{}:{}", date_range.start_date, date_range.end_date);
 
-        if let Some(cached) = self.cache.get::<Vec<ChannelData>>(&cache_key).await {
+        if let Some(cached) = self
+            .cache
+            .get::<AnnotatedReport<Vec<ChannelData>>>(&cache_key)
+            .await
+        {
             return Ok(cached);
         }
 
@@ -296,8 +405,11 @@ impl AnalyticsService {
             return_property_quota: None,
         };
 
-        let response = self.client.run_report(request).await?;
-        let channels = self.process_channels_response(response)?;
+        let (response, data_quality) = self.run_report(request, RequestPriority::Normal).await?;
+        let channels = AnnotatedReport {
+            data: self.process_channels_response(response)?,
+            data_quality,
+        };
 
         self.cache.set(&cache_key, &channels).await;
         Ok(channels)
@@ -360,7 +472,7 @@ impl AnalyticsService {
         &self,
         date_range: DateRange,
         limit: Option<i64>,
-    ) -> Result<Vec<PageData>, ClientError> {
+    ) -> Result<AnnotatedReport<Vec<PageData>>, ClientError> {
         let cache_key = format!(
             "top_pages:{}:{}:{}",
             date_range.start_date,
@@ -368,7 +480,11 @@ impl AnalyticsService {
             limit.unwrap_or(10)
         );
 
-        if let Some(cached) = self.cache.get::<Vec<PageData>>(&cache_key).await {
+        if let Some(cached) = self
+            .cache
+            .get::<AnnotatedReport<Vec<PageData>>>(&cache_key)
+            .await
+        {
             return Ok(cached);
         }
 
@@ -396,8 +512,11 @@ impl AnalyticsService {
             return_property_quota: None,
         };
 
-        let response = self.client.run_report(request).await?;
-        let pages = self.process_pages_response(response)?;
+        let (response, data_quality) = self.run_report(request, RequestPriority::High).await?;
+        let pages = AnnotatedReport {
+            data: self.process_pages_response(response)?,
+            data_quality,
+        };
 
         self.cache.set(&cache_key, &pages).await;
         Ok(pages)
@@ -440,7 +559,7 @@ impl AnalyticsService {
         &self,
         date_range: DateRange,
         limit: Option<i64>,
-    ) -> Result<Vec<ReferrerData>, ClientError> {
+    ) -> Result<AnnotatedReport<Vec<ReferrerData>>, ClientError> {
         let cache_key = format!(
             "referrers:{}:{}:{}",
             date_range.start_date,
@@ -448,7 +567,11 @@ impl AnalyticsService {
             limit.unwrap_or(10)
         );
 
-        if let Some(cached) = self.cache.get::<Vec<ReferrerData>>(&cache_key).await {
+        if let Some(cached) = self
+            .cache
+            .get::<AnnotatedReport<Vec<ReferrerData>>>(&cache_key)
+            .await
+        {
             return Ok(cached);
         }
 
@@ -478,8 +601,11 @@ impl AnalyticsService {
             return_property_quota: None,
         };
 
-        let response = self.client.run_report(request).await?;
-        let referrers = self.process_referrers_response(response)?;
+        let (response, data_quality) = self.run_report(request, RequestPriority::Normal).await?;
+        let referrers = AnnotatedReport {
+            data: self.process_referrers_response(response)?,
+            data_quality,
+        };
 
         self.cache.set(&cache_key, &referrers).await;
         Ok(referrers)
@@ -527,6 +653,70 @@ impl AnalyticsService {
         Ok(referrers)
     }
 
+    /// Run a report at the `priority` of the widget showing it, recording
+    /// the quality of the data GA returned
+    async fn run_report(
+        &self,
+        request: RunReportRequest,
+        priority: RequestPriority,
+    ) -> Result<(RunReportResponse, DataQuality), ClientError> {
+        let response = self.client.run_report_with_priority(request, priority).await?;
+        let data_quality = self.data_quality(&response);
+        if !data_quality.is_exact() {
+            debug!(warnings = ?data_quality.warnings(), "GA returned inexact data");
+        }
+        Ok((response, data_quality))
+    }
+
+    /// Run a report with a single date range, splitting the range into
+    /// chunks when the sampling policy asks for it
+    ///
+    /// Only reports whose rows are keyed by date can be split: rows of
+    /// different chunks never overlap, so they are concatenated as is.
+    async fn run_split_report(
+        &self,
+        request: RunReportRequest,
+        date_range: &DateRange,
+        priority: RequestPriority,
+    ) -> Result<(RunReportResponse, DataQuality), ClientError> {
+        let chunks = date_range.split(self.sampling.max_days_per_request);
+        if chunks.len() < 2 || self.sampling.level == SamplingLevel::Small {
+            return self.run_report(request, priority).await;
+        }
+
+        if self.sampling.level == SamplingLevel::Default {
+            let (response, data_quality) = self.run_report(request.clone(), priority).await?;
+            if !data_quality.is_sampled() {
+                return Ok((response, data_quality));
+            }
+            debug!(
+                chunks = chunks.len(),
+                "GA sampled the report, requesting the date range in chunks"
+            );
+        }
+
+        let mut responses = Vec::with_capacity(chunks.len());
+        for chunk in &chunks {
+            let mut chunk_request = request.clone();
+            chunk_request.date_ranges = vec![GoogleAnalyticsClient::build_date_range(chunk)];
+            responses.push(self.client.run_report_with_priority(chunk_request, priority).await?);
+        }
+
+        let mut data_quality = DataQuality::default();
+        for response in &responses {
+            data_quality.merge(self.data_quality(response));
+        }
+        Ok((merge_responses(responses), data_quality))
+    }
+
+    fn data_quality(&self, response: &RunReportResponse) -> DataQuality {
+        let mut data_quality = DataQuality::from_response(response);
+        if let Some(sampling) = data_quality.sampling.as_mut() {
+            sampling.sampling_level = Some(self.sampling.level);
+        }
+        data_quality
+    }
+
     // Helper methods for parsing metric values
 
     fn parse_metric_value(value: &Option<&MetricValue>) -> u64 {
@@ -548,6 +738,178 @@ impl std::fmt::Debug for AnalyticsService {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("AnalyticsService")
             .field("client", &self.client)
+            .field("sampling", &self.sampling)
             .finish()
     }
 }
+
+/// Combine the responses of the chunks of a split date range
+///
+/// Rows are concatenated in chunk order. Totals of averages and rates
+/// are weighted by the first metric of each chunk (sessions in every
+/// report that is split); other totals are summed.
+fn merge_responses(responses: Vec<RunReportResponse>) -> RunReportResponse {
+    let mut responses = responses.into_iter();
+    let Some(mut merged) = responses.next() else {
+        return RunReportResponse {
+            dimension_headers: None,
+            metric_headers: None,
+            rows: None,
+            totals: None,
+            maximums: None,
+            minimums: None,
+            row_count: None,
+            metadata: None,
+            property_quota: None,
+            kind: None,
+        };
+    };
+
+    let averaged: Vec<bool> = merged
+        .metric_headers
+        .iter()
+        .flatten()
+        .map(|header| {
+            header.name.starts_with("average")
+                || header.name.ends_with("Rate")
+                || header.name.contains("Per")
+        })
+        .collect();
+    let mut totals: Vec<Vec<f64>> = Vec::new();
+    let mut push_totals = |response: &RunReportResponse| {
+        if let Some(values) = response
+            .totals
+            .as_ref()
+            .and_then(|t| t.first())
+            .and_then(|row| row.metric_values.as_ref())
+        {
+            totals.push(
+                values
+                    .iter()
+                    .map(|v| AnalyticsService::parse_metric_float(&Some(v)))
+                    .collect(),
+            );
+        }
+    };
+    push_totals(&merged);
+
+    for response in responses {
+        push_totals(&response);
+        if let Some(rows) = response.rows {
+            merged.rows.get_or_insert_with(Vec::new).extend(rows);
+        }
+        merged.row_count = Some(merged.row_count.unwrap_or(0) + response.row_count.unwrap_or(0));
+        merged.property_quota = response.property_quota.or(merged.property_quota);
+    }
+
+    if !totals.is_empty() {
+        let metric_count = totals.iter().map(Vec::len).max().unwrap_or(0);
+        let weight = |chunk: &Vec<f64>| chunk.first().copied().unwrap_or(0.0);
+        let total_weight: f64 = totals.iter().map(weight).sum();
+        let values = (0..metric_count)
+            .map(|i| {
+                let value = |chunk: &Vec<f64>| chunk.get(i).copied().unwrap_or(0.0);
+                let combined = if !averaged.get(i).copied().unwrap_or(false) {
+                    totals.iter().map(value).sum()
+                } else if total_weight > 0.0 {
+                    totals.iter().map(|c| value(c) * weight(c)).sum::<f64>() / total_weight
+                } else {
+                    0.0
+                };
+                MetricValue {
+                    value: Some(combined.to_string()),
+                    one_value: None,
+                }
+            })
+            .collect();
+        merged.totals = Some(vec![Row {
+            dimension_values: None,
+            metric_values: Some(values),
+        }]);
+    }
+
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metric(value: &str) -> MetricValue {
+        MetricValue {
+            value: Some(value.to_string()),
+            one_value: None,
+        }
+    }
+
+    fn chunk(date: &str, sessions: &str, duration: &str, sampled: bool) -> RunReportResponse {
+        let row = |values: Vec<MetricValue>| Row {
+            dimension_values: None,
+            metric_values: Some(values),
+        };
+        RunReportResponse {
+            dimension_headers: None,
+            metric_headers: Some(vec![
+                MetricHeader {
+                    name: "sessions".to_string(),
+                    metric_type: Some(MetricType::TypeInteger),
+                },
+                MetricHeader {
+                    name: "averageSessionDuration".to_string(),
+                    metric_type: Some(MetricType::TypeSeconds),
+                },
+            ]),
+            rows: Some(vec![Row {
+                dimension_values: Some(vec![crate::models::api::DimensionValue {
+                    value: Some(date.to_string()),
+                    one_value: None,
+                }]),
+                metric_values: Some(vec![metric(sessions), metric(duration)]),
+            }]),
+            totals: Some(vec![row(vec![metric(sessions), metric(duration)])]),
+            maximums: None,
+            minimums: None,
+            row_count: Some(1),
+            metadata: Some(ResponseMetaData {
+                data_loss_from_other_row: Some(false),
+                schema_restriction_response: None,
+                currency_code: None,
+                time_zone: None,
+                subject_to_thresholding: Some(sampled),
+                sampling_metadatas: sampled.then(|| {
+                    vec![SamplingMetadata {
+                        samples_read_count: Some("250".to_string()),
+                        sampling_space_size: Some("1000".to_string()),
+                    }]
+                }),
+                privacy_redactions: None,
+            }),
+            property_quota: None,
+            kind: None,
+        }
+    }
+
+    #[test]
+    fn test_merge_split_responses() {
+        let responses = vec![
+            chunk("20240101", "10", "60", true),
+            chunk("20240201", "30", "20", false),
+        ];
+        let mut quality = DataQuality::default();
+        for response in &responses {
+            quality.merge(DataQuality::from_response(response));
+        }
+        assert_eq!(quality.split_requests, 2);
+        assert!(quality.is_sampled());
+
+        let merged = merge_responses(responses);
+        assert_eq!(merged.rows.as_ref().unwrap().len(), 2);
+        assert_eq!(merged.row_count, Some(2));
+
+        let totals = merged.totals.unwrap();
+        let values = totals[0].metric_values.as_ref().unwrap();
+        assert_eq!(values[0].value.as_deref(), Some("40"));
+        // (10 * 60 + 30 * 20) / 40 sessions
+        assert_eq!(values[1].value.as_deref(), Some("30"));
+    }
+}
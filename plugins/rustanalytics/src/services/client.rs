@@ -2,26 +2,38 @@
 //!
 //! This module provides a client for interacting with the Google Analytics Data API (GA4).
 
-use std::time::{Duration, Instant};
+use std::collections::HashMap;
+use std::sync::{Arc, Weak};
+use std::time::Duration;
 
+use async_trait::async_trait;
 use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
-use chrono::Utc;
-use reqwest::{Client, StatusCode};
+use chrono::{DateTime, Utc};
+use parking_lot::{Mutex, RwLock};
+use rand::Rng;
+use reqwest::StatusCode;
+use rustpress_core::fault::{FaultInjector, FaultTarget};
+use rustpress_core::http::HttpClient;
 use rsa::pkcs8::DecodePrivateKey;
 use rsa::signature::{SignatureEncoding, Signer};
 use rsa::RsaPrivateKey;
 use serde::{Deserialize, Serialize};
 use sha2::Sha256;
 use thiserror::Error;
-use tracing::warn;
+use tracing::{debug, warn};
 
 use crate::models::api::*;
-use crate::models::{ServiceAccountCredentials, DateRange, AvailableProperty};
+use crate::services::coordinator::{RequestCoordinator, RequestLimits, RequestPriority};
+use crate::services::demo::{DemoData, DEMO_PROPERTY_ID, DEMO_REPORT_KIND};
+use crate::services::privacy::PrivacyPolicy;
+use crate::models::{
+    ServiceAccountCredentials, DateRange, AvailableProperty, TokenHealth, TokenState,
+};
 
 /// Google Analytics API base URLs
 const GA_DATA_API_BASE: &str = "https://analyticsdata.googleapis.com/v1beta";
 const GA_ADMIN_API_BASE: &str = "https://analyticsadmin.googleapis.com/v1beta";
-const GOOGLE_TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+pub(crate) const GOOGLE_TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
 
 /// Scopes required for Google Analytics API
 const GA_SCOPES: &[&str] = &[
@@ -61,6 +73,12 @@ pub enum ClientError {
 
     #[error("RSA error: {0}")]
     RsaError(String),
+
+    #[error("Invalid delivery target: {0}")]
+    InvalidDeliveryTarget(String),
+
+    #[error("Delivery failed: {0}")]
+    DeliveryFailed(String),
 }
 
 /// JWT claims for service account authentication
@@ -73,23 +91,163 @@ struct JwtClaims {
     iat: i64,
 }
 
-/// Access token with expiry
+/// Refresh tokens this long before they expire
+const TOKEN_REFRESH_MARGIN_SECS: i64 = 300;
+
+/// Upper bound of the random delay added to the refresh margin, so instances
+/// sharing a key don't all refresh at the same moment
+const TOKEN_REFRESH_JITTER_SECS: i64 = 120;
+
+/// Lifetime assumed when the token endpoint doesn't report one
+const DEFAULT_TOKEN_LIFETIME_SECS: i64 = 3600;
+
+/// Access token minted with a service account key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredToken {
+    /// `private_key_id` of the key the token was minted with
+    pub key_id: String,
+    pub access_token: String,
+    pub issued_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    /// When to replace the token, ahead of its expiry
+    pub refresh_at: DateTime<Utc>,
+}
+
+impl StoredToken {
+    /// Token valid for `lifetime_secs` from `issued_at`, due for refresh
+    /// `jitter_secs` earlier than the usual margin. The refresh never comes
+    /// later than halfway through a short-lived token
+    pub fn issue(
+        key_id: impl Into<String>,
+        access_token: impl Into<String>,
+        issued_at: DateTime<Utc>,
+        lifetime_secs: i64,
+        jitter_secs: i64,
+    ) -> Self {
+        let lifetime_secs = if lifetime_secs > 0 { lifetime_secs } else { DEFAULT_TOKEN_LIFETIME_SECS };
+        let lead = (TOKEN_REFRESH_MARGIN_SECS + jitter_secs.max(0)).min(lifetime_secs / 2);
+        let expires_at = issued_at + chrono::Duration::seconds(lifetime_secs);
+
+        Self {
+            key_id: key_id.into(),
+            access_token: access_token.into(),
+            issued_at,
+            expires_at,
+            refresh_at: expires_at - chrono::Duration::seconds(lead),
+        }
+    }
+
+    /// Whether the token can no longer be used
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        now >= self.expires_at
+    }
+
+    /// Whether the token should be replaced
+    pub fn needs_refresh(&self, now: DateTime<Utc>) -> bool {
+        now >= self.refresh_at
+    }
+}
+
+/// Persistence for access tokens, so restarts and sibling instances reuse a
+/// token instead of minting a new one each time
+#[async_trait]
+pub trait TokenStore: Send + Sync {
+    /// Latest token minted with the key `key_id`
+    async fn load(&self, key_id: &str) -> Result<Option<StoredToken>, ClientError>;
+
+    /// Store `token`, replacing the one kept for its key
+    async fn save(&self, token: &StoredToken) -> Result<(), ClientError>;
+
+    /// Drop tokens expired at `now`, returning how many were dropped
+    async fn purge_expired(&self, now: DateTime<Utc>) -> Result<usize, ClientError>;
+}
+
+/// Token store kept in process memory
+#[derive(Debug, Default)]
+pub struct MemoryTokenStore {
+    tokens: RwLock<HashMap<String, StoredToken>>,
+}
+
+impl MemoryTokenStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl TokenStore for MemoryTokenStore {
+    async fn load(&self, key_id: &str) -> Result<Option<StoredToken>, ClientError> {
+        Ok(self.tokens.read().get(key_id).cloned())
+    }
+
+    async fn save(&self, token: &StoredToken) -> Result<(), ClientError> {
+        self.tokens.write().insert(token.key_id.clone(), token.clone());
+        Ok(())
+    }
+
+    async fn purge_expired(&self, now: DateTime<Utc>) -> Result<usize, ClientError> {
+        let mut tokens = self.tokens.write();
+        let before = tokens.len();
+        tokens.retain(|_, token| !token.is_expired(now));
+        Ok(before - tokens.len())
+    }
+}
+
+/// Service account keys the client may mint tokens with
 #[derive(Debug, Clone)]
-struct AccessToken {
-    token: String,
-    expires_at: Instant,
+struct KeyRing {
+    current: ServiceAccountCredentials,
+    /// Key replaced by a rotation, used as a fallback until it retires
+    previous: Option<RetiringKey>,
+}
+
+#[derive(Debug, Clone)]
+struct RetiringKey {
+    credentials: ServiceAccountCredentials,
+    retires_at: DateTime<Utc>,
+}
+
+impl KeyRing {
+    /// Previous key, if still within its grace window at `now`
+    fn fallback(&self, now: DateTime<Utc>) -> Option<&ServiceAccountCredentials> {
+        self.previous
+            .as_ref()
+            .filter(|key| key.retires_at > now)
+            .map(|key| &key.credentials)
+    }
+}
+
+/// Outcome of recent token refreshes
+#[derive(Debug, Default)]
+struct RefreshStatus {
+    last_refresh: Option<DateTime<Utc>>,
+    last_error: Option<String>,
+    consecutive_failures: u32,
 }
 
 /// Google Analytics API Client
 pub struct GoogleAnalyticsClient {
-    /// HTTP client
-    http_client: Client,
+    /// Shared outbound HTTP client
+    http_client: HttpClient,
     /// GA4 Property ID
     property_id: String,
-    /// Service account credentials
-    credentials: Option<ServiceAccountCredentials>,
+    /// Service account keys
+    keys: RwLock<Option<KeyRing>>,
     /// Current access token
-    access_token: tokio::sync::RwLock<Option<AccessToken>>,
+    token: RwLock<Option<StoredToken>>,
+    /// Where minted tokens are persisted
+    token_store: Arc<dyn TokenStore>,
+    /// Serializes refreshes so concurrent requests mint a single token
+    refresh_lock: tokio::sync::Mutex<()>,
+    refresh_status: Mutex<RefreshStatus>,
+    /// Faults injected into API requests (development and staging only)
+    faults: FaultInjector,
+    /// Concurrency limit, priorities and report batching
+    coordinator: RequestCoordinator,
+    /// Synthetic data answering every request instead of the API
+    demo: Option<DemoData>,
+    /// Dimensions withheld and rows hidden from every report
+    privacy: PrivacyPolicy,
 }
 
 impl GoogleAnalyticsClient {
@@ -98,87 +256,244 @@ impl GoogleAnalyticsClient {
         property_id: String,
         service_account_json: Option<String>,
     ) -> Result<Self, ClientError> {
-        let http_client = Client::builder()
-            .timeout(Duration::from_secs(30))
-            .connect_timeout(Duration::from_secs(10))
-            .build()?;
-
-        let credentials = if let Some(json) = service_account_json {
-            Some(serde_json::from_str::<ServiceAccountCredentials>(&json)
-                .map_err(|e| ClientError::InvalidCredentials(e.to_string()))?)
-        } else {
-            None
+        Self::with_http_client(HttpClient::default(), property_id, service_account_json).await
+    }
+
+    /// Create a client sending its requests through `http_client`, so the
+    /// host's per-destination timeouts, retries and circuit breakers apply
+    pub async fn with_http_client(
+        http_client: HttpClient,
+        property_id: String,
+        service_account_json: Option<String>,
+    ) -> Result<Self, ClientError> {
+        Self::with_token_store(
+            http_client,
+            property_id,
+            service_account_json,
+            Arc::new(MemoryTokenStore::new()),
+        )
+        .await
+    }
+
+    /// Create a client persisting its access tokens in `token_store`
+    pub async fn with_token_store(
+        http_client: HttpClient,
+        property_id: String,
+        service_account_json: Option<String>,
+        token_store: Arc<dyn TokenStore>,
+    ) -> Result<Self, ClientError> {
+        let credentials = match service_account_json {
+            Some(json) => Some(parse_credentials(&json)?),
+            None => None,
         };
 
         let client = Self {
             http_client,
             property_id,
-            credentials,
-            access_token: tokio::sync::RwLock::new(None),
+            keys: RwLock::new(credentials.map(|current| KeyRing { current, previous: None })),
+            token: RwLock::new(None),
+            token_store,
+            refresh_lock: tokio::sync::Mutex::new(()),
+            refresh_status: Mutex::new(RefreshStatus::default()),
+            faults: FaultInjector::disabled(),
+            coordinator: RequestCoordinator::default(),
+            demo: None,
+            privacy: PrivacyPolicy::default(),
         };
 
         // Validate connection
-        if client.credentials.is_some() {
+        if client.keys.read().is_some() {
             client.get_access_token().await?;
         }
 
         Ok(client)
     }
 
+    /// Create a client answering every request with synthetic data
+    /// generated from `seed`, without a property or credentials
+    pub fn demo(seed: u64) -> Self {
+        Self {
+            http_client: HttpClient::default(),
+            property_id: DEMO_PROPERTY_ID.to_string(),
+            keys: RwLock::new(None),
+            token: RwLock::new(None),
+            token_store: Arc::new(MemoryTokenStore::new()),
+            refresh_lock: tokio::sync::Mutex::new(()),
+            refresh_status: Mutex::new(RefreshStatus::default()),
+            faults: FaultInjector::disabled(),
+            coordinator: RequestCoordinator::default(),
+            demo: Some(DemoData::new(seed)),
+            privacy: PrivacyPolicy::default(),
+        }
+    }
+
+    /// Whether the client serves synthetic data
+    pub fn is_demo(&self) -> bool {
+        self.demo.is_some()
+    }
+
+    /// Inject faults from `faults` into API requests
+    pub fn with_faults(mut self, faults: FaultInjector) -> Self {
+        self.faults = faults;
+        self
+    }
+
+    /// Limit concurrent requests and batch reports according to `limits`
+    pub fn with_request_limits(mut self, limits: RequestLimits) -> Self {
+        self.coordinator = RequestCoordinator::new(limits);
+        self
+    }
+
+    /// Withhold data from every report according to `privacy`
+    pub fn with_privacy(mut self, privacy: PrivacyPolicy) -> Self {
+        self.privacy = privacy;
+        self
+    }
+
+    /// Privacy controls applied to every report
+    pub fn privacy(&self) -> &PrivacyPolicy {
+        &self.privacy
+    }
+
+    /// Limits on concurrent requests and report batches
+    pub fn request_limits(&self) -> RequestLimits {
+        self.coordinator.limits()
+    }
+
     /// Get the property ID
     pub fn property_id(&self) -> &str {
         &self.property_id
     }
 
-    /// Get or refresh access token
+    /// Get the current access token, refreshing it when it's due. A failed
+    /// refresh keeps serving the current token until it actually expires
     async fn get_access_token(&self) -> Result<String, ClientError> {
-        // Check if we have a valid token
+        if let Some(token) = self.current_token() {
+            if !token.needs_refresh(Utc::now()) {
+                return Ok(token.access_token);
+            }
+        }
+
+        let _refreshing = self.refresh_lock.lock().await;
+
+        // Another request may have refreshed the token while we waited
+        let cached = self.current_token();
+        if let Some(ref token) = cached {
+            if !token.needs_refresh(Utc::now()) {
+                return Ok(token.access_token.clone());
+            }
+        }
+
+        match self.refresh_token().await {
+            Ok(token) => Ok(token.access_token),
+            Err(e) => match cached {
+                Some(token) if !token.is_expired(Utc::now()) => {
+                    warn!(error = %e, expires_at = %token.expires_at, "Token refresh failed, using current token until it expires");
+                    Ok(token.access_token)
+                }
+                _ => Err(e),
+            },
+        }
+    }
+
+    fn current_token(&self) -> Option<StoredToken> {
+        self.token.read().clone()
+    }
+
+    /// Replace the current token, reusing a stored one for the current key
+    /// when it's still fresh, minting with the current key otherwise and
+    /// falling back to the previous key during its grace window. Callers
+    /// hold `refresh_lock`
+    async fn refresh_token(&self) -> Result<StoredToken, ClientError> {
+        let (current, fallback) = {
+            let keys = self.keys.read();
+            let keys = keys.as_ref()
+                .ok_or_else(|| ClientError::InvalidCredentials("No credentials configured".to_string()))?;
+            (keys.current.clone(), keys.fallback(Utc::now()).cloned())
+        };
+
+        if let Some(token) = self.stored_token(&current.private_key_id).await {
+            *self.token.write() = Some(token.clone());
+            return Ok(token);
+        }
+
+        let result = match self.mint_token(&current).await {
+            Ok(token) => Ok(token),
+            Err(e) => match fallback {
+                Some(previous) => {
+                    warn!(error = %e, key_id = %previous.private_key_id, "Minting with the current key failed, using the previous key");
+                    self.mint_token(&previous).await.map_err(|_| e)
+                }
+                None => Err(e),
+            },
+        };
+
         {
-            let token = self.access_token.read().await;
-            if let Some(ref t) = *token {
-                if t.expires_at > Instant::now() {
-                    return Ok(t.token.clone());
+            let mut status = self.refresh_status.lock();
+            match &result {
+                Ok(token) => {
+                    status.last_refresh = Some(token.issued_at);
+                    status.last_error = None;
+                    status.consecutive_failures = 0;
+                }
+                Err(e) => {
+                    status.last_error = Some(e.to_string());
+                    status.consecutive_failures += 1;
                 }
             }
         }
 
-        // Need to refresh token
-        let new_token = self.refresh_access_token().await?;
+        let token = result?;
+        if let Err(e) = self.token_store.save(&token).await {
+            warn!(error = %e, "Failed to persist access token");
+        }
+        *self.token.write() = Some(token.clone());
 
-        let mut token = self.access_token.write().await;
-        *token = Some(AccessToken {
-            token: new_token.clone(),
-            expires_at: Instant::now() + Duration::from_secs(3500), // Token valid for ~1 hour
-        });
+        Ok(token)
+    }
 
-        Ok(new_token)
+    /// Stored token for `key_id` that isn't due for refresh yet
+    async fn stored_token(&self, key_id: &str) -> Option<StoredToken> {
+        match self.token_store.load(key_id).await {
+            Ok(token) => token.filter(|token| !token.needs_refresh(Utc::now())),
+            Err(e) => {
+                warn!(error = %e, "Failed to load stored access token");
+                None
+            }
+        }
     }
 
-    /// Refresh the access token using service account credentials
-    async fn refresh_access_token(&self) -> Result<String, ClientError> {
-        let credentials = self.credentials.as_ref()
-            .ok_or_else(|| ClientError::InvalidCredentials("No credentials configured".to_string()))?;
+    /// Mint an access token with the service account key `credentials`
+    async fn mint_token(&self, credentials: &ServiceAccountCredentials) -> Result<StoredToken, ClientError> {
+        let token_uri = if credentials.token_uri.is_empty() {
+            GOOGLE_TOKEN_URL
+        } else {
+            credentials.token_uri.as_str()
+        };
 
-        let now = Utc::now().timestamp();
+        let issued_at = Utc::now();
+        let now = issued_at.timestamp();
         let claims = JwtClaims {
             iss: credentials.client_email.clone(),
             scope: GA_SCOPES.join(" "),
-            aud: GOOGLE_TOKEN_URL.to_string(),
+            aud: token_uri.to_string(),
             iat: now,
-            exp: now + 3600,
+            exp: now + DEFAULT_TOKEN_LIFETIME_SECS,
         };
 
         // Create JWT manually using RSA
         let jwt = self.create_jwt(&claims, &credentials.private_key)?;
 
-        let response = self.http_client
-            .post(GOOGLE_TOKEN_URL)
+        let request = self.http_client
+            .post(token_uri)
             .form(&[
                 ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
                 ("assertion", &jwt),
-            ])
-            .send()
-            .await?;
+            ]);
+        let response = self.http_client
+            .send(request)
+            .await
+            .map_err(|e| ClientError::RequestFailed(e.to_string()))?;
 
         if !response.status().is_success() {
             let error_text = response.text().await.unwrap_or_default();
@@ -186,7 +501,135 @@ impl GoogleAnalyticsClient {
         }
 
         let token_response: TokenResponse = response.json().await?;
-        Ok(token_response.access_token)
+        let jitter = rand::thread_rng().gen_range(0..=TOKEN_REFRESH_JITTER_SECS);
+
+        Ok(StoredToken::issue(
+            credentials.private_key_id.clone(),
+            token_response.access_token,
+            issued_at,
+            token_response.expires_in,
+            jitter,
+        ))
+    }
+
+    /// Switch to a new service account key without dropping requests. A token
+    /// is minted with the new key before it replaces the current one; the
+    /// replaced key stays usable as a fallback for `grace`
+    pub async fn rotate_key(
+        &self,
+        service_account_json: &str,
+        grace: chrono::Duration,
+    ) -> Result<(), ClientError> {
+        let credentials = parse_credentials(service_account_json)?;
+
+        let _refreshing = self.refresh_lock.lock().await;
+        let token = self.mint_token(&credentials).await?;
+
+        {
+            let mut keys = self.keys.write();
+            let previous = keys.take().map(|keys| RetiringKey {
+                credentials: keys.current,
+                retires_at: Utc::now() + grace,
+            });
+            *keys = Some(KeyRing {
+                current: credentials,
+                previous: previous.filter(|key| key.credentials.private_key_id != token.key_id),
+            });
+        }
+
+        {
+            let mut status = self.refresh_status.lock();
+            status.last_refresh = Some(token.issued_at);
+            status.last_error = None;
+            status.consecutive_failures = 0;
+        }
+
+        if let Err(e) = self.token_store.save(&token).await {
+            warn!(error = %e, "Failed to persist access token");
+        }
+        *self.token.write() = Some(token);
+
+        Ok(())
+    }
+
+    /// One round of token upkeep: retire the previous key once its grace
+    /// window is over, purge expired stored tokens and refresh the current
+    /// token when it's due
+    pub async fn maintain_tokens(&self) -> Result<(), ClientError> {
+        let now = Utc::now();
+        {
+            let mut keys = self.keys.write();
+            match keys.as_mut() {
+                Some(keys) => {
+                    if keys.previous.as_ref().is_some_and(|key| key.retires_at <= now) {
+                        keys.previous = None;
+                    }
+                }
+                None => return Ok(()),
+            }
+        }
+
+        match self.token_store.purge_expired(now).await {
+            Ok(0) => {}
+            Ok(purged) => debug!(purged, "Purged expired access tokens"),
+            Err(e) => warn!(error = %e, "Failed to purge expired access tokens"),
+        }
+
+        let due = self.current_token().is_none_or(|token| token.needs_refresh(now));
+        if due {
+            self.get_access_token().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Run `maintain_tokens` every `period` in the background, until the
+    /// client is dropped
+    pub fn spawn_token_maintenance(self: &Arc<Self>, period: Duration) -> tokio::task::JoinHandle<()> {
+        let client: Weak<Self> = Arc::downgrade(self);
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            // The first tick completes immediately; the constructor just minted a token
+            interval.tick().await;
+
+            loop {
+                interval.tick().await;
+                let Some(client) = client.upgrade() else { break };
+                if let Err(e) = client.maintain_tokens().await {
+                    warn!(error = %e, "Access token maintenance failed");
+                }
+            }
+        })
+    }
+
+    /// Health of the access token, for the connection status
+    pub fn token_health(&self) -> TokenHealth {
+        let now = Utc::now();
+        let token = self.current_token();
+        let keys = self.keys.read().clone();
+        let status = self.refresh_status.lock();
+
+        let state = match &token {
+            None => TokenState::Missing,
+            Some(token) if token.is_expired(now) => TokenState::Expired,
+            Some(token) if token.needs_refresh(now) => TokenState::RefreshDue,
+            Some(_) => TokenState::Valid,
+        };
+        let previous = keys.as_ref().and_then(|keys| keys.previous.as_ref());
+
+        TokenHealth {
+            state,
+            key_id: keys.as_ref().map(|keys| keys.current.private_key_id.clone()),
+            expires_at: token.as_ref().map(|token| token.expires_at),
+            refresh_at: token.as_ref().map(|token| token.refresh_at),
+            last_refresh: status.last_refresh,
+            last_refresh_error: status.last_error.clone(),
+            consecutive_failures: status.consecutive_failures,
+            previous_key_id: previous.map(|key| key.credentials.private_key_id.clone()),
+            previous_key_retires_at: previous.map(|key| key.retires_at),
+        }
     }
 
     /// Create a JWT token using RS256
@@ -221,13 +664,33 @@ impl GoogleAnalyticsClient {
         Ok(format!("{}.{}", signing_input, signature_b64))
     }
 
-    /// Make an authenticated API request
+    /// Make an authenticated API request once the concurrency limit
+    /// allows it
     async fn request<T: for<'de> Deserialize<'de>>(
         &self,
         method: reqwest::Method,
         url: &str,
         body: Option<&impl Serialize>,
     ) -> Result<T, ClientError> {
+        let _permit = self.coordinator.acquire(RequestPriority::Normal).await;
+        self.send_request(method, url, body).await
+    }
+
+    /// Make an authenticated API request right away; callers hold a permit
+    async fn send_request<T: for<'de> Deserialize<'de>>(
+        &self,
+        method: reqwest::Method,
+        url: &str,
+        body: Option<&impl Serialize>,
+    ) -> Result<T, ClientError> {
+        let host = reqwest::Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string));
+        self.faults
+            .inject(FaultTarget::Http, host.as_deref())
+            .await
+            .map_err(|e| ClientError::RequestFailed(e.to_string()))?;
+
         let token = self.get_access_token().await?;
 
         let mut request = self.http_client
@@ -238,7 +701,10 @@ impl GoogleAnalyticsClient {
             request = request.json(b);
         }
 
-        let response = request.send().await?;
+        let response = self.http_client
+            .send(request)
+            .await
+            .map_err(|e| ClientError::RequestFailed(e.to_string()))?;
         let status = response.status();
 
         match status {
@@ -278,35 +744,106 @@ impl GoogleAnalyticsClient {
 
     /// Run a report
     pub async fn run_report(&self, request: RunReportRequest) -> Result<RunReportResponse, ClientError> {
+        self.run_report_with_priority(request, RequestPriority::Normal).await
+    }
+
+    /// Run a report, ahead of waiting requests of lower `priority`. Reports
+    /// waiting together are sent as one batch
+    pub async fn run_report_with_priority(
+        &self,
+        mut request: RunReportRequest,
+        priority: RequestPriority,
+    ) -> Result<RunReportResponse, ClientError> {
+        let prepared = self.privacy.prepare(&mut request)?;
+        let mut response = self.coordinator
+            .run_report(priority, request, |requests| self.send_reports(requests))
+            .await?;
+        self.privacy.apply(&prepared, &mut response);
+        Ok(response)
+    }
+
+    /// Send reports in a single call; callers hold a permit
+    async fn send_reports(
+        &self,
+        mut requests: Vec<RunReportRequest>,
+    ) -> Result<Vec<RunReportResponse>, ClientError> {
+        if let Some(demo) = &self.demo {
+            return requests.iter().map(|request| demo.run_report(request)).collect();
+        }
+
+        if requests.len() == 1 {
+            let url = format!(
+                "{}/properties/{}:runReport",
+                GA_DATA_API_BASE, self.property_id
+            );
+            let request = requests.remove(0);
+            let response = self.send_request(reqwest::Method::POST, &url, Some(&request)).await?;
+            return Ok(vec![response]);
+        }
+
         let url = format!(
-            "{}/properties/{}:runReport",
+            "{}/properties/{}:batchRunReports",
             GA_DATA_API_BASE, self.property_id
         );
-        self.request(reqwest::Method::POST, &url, Some(&request)).await
+        let batch = BatchRunReportsRequest {
+            property: format!("properties/{}", self.property_id),
+            requests,
+        };
+        debug!(reports = batch.requests.len(), "Sending batched GA reports");
+        let response: BatchRunReportsResponse = self
+            .send_request(reqwest::Method::POST, &url, Some(&batch))
+            .await?;
+        Ok(response.reports.unwrap_or_default())
     }
 
     /// Run a real-time report
     pub async fn run_realtime_report(
         &self,
-        request: RunRealtimeReportRequest,
+        mut request: RunRealtimeReportRequest,
     ) -> Result<RunRealtimeReportResponse, ClientError> {
-        let url = format!(
-            "{}/properties/{}:runRealtimeReport",
-            GA_DATA_API_BASE, self.property_id
-        );
-        self.request(reqwest::Method::POST, &url, Some(&request)).await
+        let prepared = self.privacy.prepare_realtime(&mut request)?;
+        let mut response = match &self.demo {
+            Some(demo) => demo.run_realtime_report(&request)?,
+            None => {
+                let url = format!(
+                    "{}/properties/{}:runRealtimeReport",
+                    GA_DATA_API_BASE, self.property_id
+                );
+                self.request(reqwest::Method::POST, &url, Some(&request)).await?
+            }
+        };
+        self.privacy.apply_realtime(&prepared, &mut response);
+        Ok(response)
     }
 
     /// Run batch reports
     pub async fn batch_run_reports(
         &self,
-        request: BatchRunReportsRequest,
+        mut request: BatchRunReportsRequest,
     ) -> Result<BatchRunReportsResponse, ClientError> {
-        let url = format!(
-            "{}/properties/{}:batchRunReports",
-            GA_DATA_API_BASE, self.property_id
-        );
-        self.request(reqwest::Method::POST, &url, Some(&request)).await
+        let prepared = request
+            .requests
+            .iter_mut()
+            .map(|r| self.privacy.prepare(r))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut response = match &self.demo {
+            Some(demo) => BatchRunReportsResponse {
+                reports: Some(request.requests.iter().map(|r| demo.run_report(r)).collect::<Result<_, _>>()?),
+                kind: Some(DEMO_REPORT_KIND.to_string()),
+            },
+            None => {
+                let url = format!(
+                    "{}/properties/{}:batchRunReports",
+                    GA_DATA_API_BASE, self.property_id
+                );
+                self.request(reqwest::Method::POST, &url, Some(&request)).await?
+            }
+        };
+        for (report, prepared) in response.reports.iter_mut().flatten().zip(&prepared) {
+            self.privacy.apply(prepared, report);
+        }
+        Ok(response)
     }
 
     /// Run a pivot report
@@ -314,6 +851,11 @@ impl GoogleAnalyticsClient {
         &self,
         request: RunPivotReportRequest,
     ) -> Result<RunPivotReportResponse, ClientError> {
+        self.privacy.check_pivot(&request)?;
+        if let Some(demo) = &self.demo {
+            return demo.run_pivot_report(&request);
+        }
+
         let url = format!(
             "{}/properties/{}:runPivotReport",
             GA_DATA_API_BASE, self.property_id
@@ -326,6 +868,11 @@ impl GoogleAnalyticsClient {
         &self,
         request: RunFunnelReportRequest,
     ) -> Result<serde_json::Value, ClientError> {
+        self.privacy.check_funnel(&request)?;
+        if let Some(demo) = &self.demo {
+            return demo.run_funnel_report(&request);
+        }
+
         let url = format!(
             "{}/properties/{}:runFunnelReport",
             GA_DATA_API_BASE, self.property_id
@@ -335,6 +882,10 @@ impl GoogleAnalyticsClient {
 
     /// Get metadata for available dimensions and metrics
     pub async fn get_metadata(&self) -> Result<Metadata, ClientError> {
+        if let Some(demo) = &self.demo {
+            return Ok(demo.metadata());
+        }
+
         let url = format!(
             "{}/properties/{}/metadata",
             GA_DATA_API_BASE, self.property_id
@@ -344,6 +895,10 @@ impl GoogleAnalyticsClient {
 
     /// List available account summaries
     pub async fn list_account_summaries(&self) -> Result<ListAccountSummariesResponse, ClientError> {
+        if let Some(demo) = &self.demo {
+            return Ok(demo.account_summaries());
+        }
+
         let url = format!("{}/accountSummaries", GA_ADMIN_API_BASE);
         self.request::<ListAccountSummariesResponse>(reqwest::Method::GET, &url, None::<&()>).await
     }
@@ -521,11 +1076,17 @@ impl GoogleAnalyticsClient {
     }
 }
 
+/// Parse a service account key file
+fn parse_credentials(json: &str) -> Result<ServiceAccountCredentials, ClientError> {
+    serde_json::from_str::<ServiceAccountCredentials>(json)
+        .map_err(|e| ClientError::InvalidCredentials(e.to_string()))
+}
+
 impl std::fmt::Debug for GoogleAnalyticsClient {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("GoogleAnalyticsClient")
             .field("property_id", &self.property_id)
-            .field("has_credentials", &self.credentials.is_some())
+            .field("has_credentials", &self.keys.read().is_some())
             .finish()
     }
 }
@@ -0,0 +1,331 @@
+//! Request Coordinator
+//!
+//! Shapes the GA requests a dashboard fires at once. No more than a fixed
+//! number of requests run at a time, waiting requests start in priority
+//! order so widgets above the fold load first, and report requests waiting
+//! together go out as a single batchRunReports call.
+
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+
+use crate::models::api::{RunReportRequest, RunReportResponse};
+use crate::services::client::ClientError;
+
+/// Most reports GA accepts in one batchRunReports call
+pub const MAX_BATCH_SIZE: usize = 5;
+
+/// Order in which waiting requests are sent
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RequestPriority {
+    /// Work nobody is waiting on, such as syncs
+    Low,
+    #[default]
+    Normal,
+    /// Widgets just below the fold of the dashboard
+    High,
+    /// Widgets above the fold of the dashboard
+    Critical,
+}
+
+/// Limits the coordinator enforces
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequestLimits {
+    /// Requests running at the same time, across all API calls
+    pub max_concurrent: usize,
+    /// Reports sent in one batch, at most `MAX_BATCH_SIZE`
+    pub max_batch_size: usize,
+    /// How long a report request waits for others to batch with
+    pub batch_window: Duration,
+}
+
+impl Default for RequestLimits {
+    fn default() -> Self {
+        Self {
+            max_concurrent: 4,
+            max_batch_size: MAX_BATCH_SIZE,
+            batch_window: Duration::from_millis(5),
+        }
+    }
+}
+
+/// Queue position: higher priorities first, then first come, first served
+type QueueKey = (Reverse<RequestPriority>, u64);
+
+/// Report request waiting to be sent
+struct QueuedReport {
+    request: RunReportRequest,
+    reply: oneshot::Sender<Result<RunReportResponse, ClientError>>,
+}
+
+#[derive(Default)]
+struct State {
+    /// Requests holding a permit
+    in_flight: usize,
+    next_seq: u64,
+    /// Requests waiting for a permit
+    waiters: BTreeMap<QueueKey, oneshot::Sender<()>>,
+    /// Report requests waiting to be sent, alone or in a batch
+    reports: BTreeMap<QueueKey, QueuedReport>,
+}
+
+impl State {
+    fn key(&mut self, priority: RequestPriority) -> QueueKey {
+        self.next_seq += 1;
+        (Reverse(priority), self.next_seq)
+    }
+}
+
+/// Concurrency limiter and report batcher for a Google Analytics client
+pub struct RequestCoordinator {
+    limits: RequestLimits,
+    state: Mutex<State>,
+}
+
+/// Right to run one request; the next waiting request gets it when dropped
+pub struct Permit<'a> {
+    coordinator: &'a RequestCoordinator,
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        self.coordinator.release();
+    }
+}
+
+/// Permit request in the queue; hands the permit on if it was granted
+/// after the caller stopped waiting
+struct Waiting<'a> {
+    coordinator: &'a RequestCoordinator,
+    rx: oneshot::Receiver<()>,
+    granted: bool,
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        if !self.granted {
+            self.rx.close();
+            if self.rx.try_recv().is_ok() {
+                self.coordinator.release();
+            }
+        }
+    }
+}
+
+/// Report request in the queue; leaves it when the caller stops waiting
+struct ReportTicket<'a> {
+    coordinator: &'a RequestCoordinator,
+    key: QueueKey,
+}
+
+impl Drop for ReportTicket<'_> {
+    fn drop(&mut self) {
+        self.coordinator.state.lock().reports.remove(&self.key);
+    }
+}
+
+impl RequestCoordinator {
+    /// Create a coordinator enforcing `limits`
+    pub fn new(limits: RequestLimits) -> Self {
+        Self {
+            limits: RequestLimits {
+                max_concurrent: limits.max_concurrent.max(1),
+                max_batch_size: limits.max_batch_size.clamp(1, MAX_BATCH_SIZE),
+                batch_window: limits.batch_window,
+            },
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    /// Limits in effect
+    pub fn limits(&self) -> RequestLimits {
+        self.limits
+    }
+
+    /// Requests currently running
+    pub fn in_flight(&self) -> usize {
+        self.state.lock().in_flight
+    }
+
+    /// Wait for a permit to run a request. Permits go to waiting requests
+    /// by priority, then in arrival order
+    pub async fn acquire(&self, priority: RequestPriority) -> Permit<'_> {
+        loop {
+            let rx = {
+                let mut state = self.state.lock();
+                state.waiters.retain(|_, tx| !tx.is_closed());
+                if state.in_flight < self.limits.max_concurrent && state.waiters.is_empty() {
+                    state.in_flight += 1;
+                    return Permit { coordinator: self };
+                }
+
+                let key = state.key(priority);
+                let (tx, rx) = oneshot::channel();
+                state.waiters.insert(key, tx);
+                rx
+            };
+
+            let mut waiting = Waiting { coordinator: self, rx, granted: false };
+            if (&mut waiting.rx).await.is_ok() {
+                waiting.granted = true;
+                return Permit { coordinator: self };
+            }
+        }
+    }
+
+    /// Hand the permit to the next waiting request, or free it
+    fn release(&self) {
+        let mut state = self.state.lock();
+        while let Some((_, tx)) = state.waiters.pop_first() {
+            if tx.send(()).is_ok() {
+                return;
+            }
+        }
+        state.in_flight = state.in_flight.saturating_sub(1);
+    }
+
+    /// Run a report, possibly batched with other waiting ones
+    ///
+    /// `send` receives the reports to send, at most `max_batch_size` of them
+    /// for the same property, and returns their responses in order. When
+    /// the caller that sent a batch stops waiting before it completes, the
+    /// other reports of the batch are queued again.
+    pub async fn run_report<F, Fut>(
+        &self,
+        priority: RequestPriority,
+        request: RunReportRequest,
+        send: F,
+    ) -> Result<RunReportResponse, ClientError>
+    where
+        F: Fn(Vec<RunReportRequest>) -> Fut,
+        Fut: Future<Output = Result<Vec<RunReportResponse>, ClientError>>,
+    {
+        loop {
+            let (ticket, mut reply) = self.enqueue(priority, request.clone());
+
+            if self.limits.max_batch_size > 1 && !self.limits.batch_window.is_zero() {
+                tokio::time::sleep(self.limits.batch_window).await;
+            }
+
+            // Another request may send ours while we wait for a permit
+            let permit = tokio::select! {
+                result = &mut reply => match result {
+                    Ok(result) => return result,
+                    Err(_) => continue,
+                },
+                permit = self.acquire(priority) => permit,
+            };
+
+            let Some(batch) = self.take_batch(&ticket) else {
+                drop(permit);
+                match reply.await {
+                    Ok(result) => return result,
+                    Err(_) => continue,
+                }
+            };
+
+            let (requests, replies): (Vec<_>, Vec<_>) = batch
+                .into_iter()
+                .map(|report| (report.request, report.reply))
+                .unzip();
+            let count = requests.len();
+            let result = send(requests).await;
+            drop(permit);
+
+            let mut results: Vec<Result<RunReportResponse, ClientError>> = match result {
+                Ok(responses) if responses.len() == count => responses.into_iter().map(Ok).collect(),
+                Ok(responses) => {
+                    let message = format!("Expected {} reports in the batch, got {}", count, responses.len());
+                    (0..count).map(|_| Err(ClientError::InvalidResponse(message.clone()))).collect()
+                }
+                Err(e) => {
+                    let mut results: Vec<_> = (1..count).map(|_| Err(shared_error(&e))).collect();
+                    results.insert(0, Err(e));
+                    results
+                }
+            };
+
+            // Our report comes first; the others go back to their callers
+            let own = results.remove(0);
+            for (reply, result) in replies.into_iter().skip(1).zip(results) {
+                let _ = reply.send(result);
+            }
+            return own;
+        }
+    }
+
+    fn enqueue(
+        &self,
+        priority: RequestPriority,
+        request: RunReportRequest,
+    ) -> (ReportTicket<'_>, oneshot::Receiver<Result<RunReportResponse, ClientError>>) {
+        let (reply, rx) = oneshot::channel();
+        let mut state = self.state.lock();
+        let key = state.key(priority);
+        state.reports.insert(key, QueuedReport { request, reply });
+        (ReportTicket { coordinator: self, key }, rx)
+    }
+
+    /// Take the ticket's report out of the queue, followed by the next
+    /// waiting reports for the same property. None when another request
+    /// already took it
+    fn take_batch(&self, ticket: &ReportTicket<'_>) -> Option<Vec<QueuedReport>> {
+        let mut state = self.state.lock();
+        let own = state.reports.remove(&ticket.key)?;
+
+        let batched: Vec<QueueKey> = state
+            .reports
+            .iter()
+            .filter(|(_, report)| report.request.property == own.request.property)
+            .map(|(key, _)| *key)
+            .take(self.limits.max_batch_size - 1)
+            .collect();
+
+        let mut batch = vec![own];
+        batch.extend(batched.iter().filter_map(|key| state.reports.remove(key)));
+        Some(batch)
+    }
+}
+
+impl Default for RequestCoordinator {
+    fn default() -> Self {
+        Self::new(RequestLimits::default())
+    }
+}
+
+impl std::fmt::Debug for RequestCoordinator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = self.state.lock();
+        f.debug_struct("RequestCoordinator")
+            .field("limits", &self.limits)
+            .field("in_flight", &state.in_flight)
+            .field("waiting", &state.waiters.len())
+            .field("queued_reports", &state.reports.len())
+            .finish()
+    }
+}
+
+/// Copy of a batch error for the other reports of the batch
+fn shared_error(e: &ClientError) -> ClientError {
+    match e {
+        ClientError::AuthenticationFailed(m) => ClientError::AuthenticationFailed(m.clone()),
+        ClientError::RequestFailed(m) => ClientError::RequestFailed(m.clone()),
+        ClientError::InvalidResponse(m) => ClientError::InvalidResponse(m.clone()),
+        ClientError::RateLimited(secs) => ClientError::RateLimited(*secs),
+        ClientError::QuotaExceeded(m) => ClientError::QuotaExceeded(m.clone()),
+        ClientError::PropertyNotFound(m) => ClientError::PropertyNotFound(m.clone()),
+        ClientError::InvalidCredentials(m) => ClientError::InvalidCredentials(m.clone()),
+        ClientError::RsaError(m) => ClientError::RsaError(m.clone()),
+        ClientError::InvalidDeliveryTarget(m) => ClientError::InvalidDeliveryTarget(m.clone()),
+        ClientError::DeliveryFailed(m) => ClientError::DeliveryFailed(m.clone()),
+        ClientError::NetworkError(_) | ClientError::JsonError(_) => {
+            ClientError::RequestFailed(e.to_string())
+        }
+    }
+}
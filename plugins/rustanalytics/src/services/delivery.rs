@@ -0,0 +1,746 @@
+//! Report Delivery
+//!
+//! Pushes scheduled report results to targets besides email: a Google
+//! Sheet, an SFTP drop or an S3 bucket, as CSV or Parquet. Target
+//! credentials are kept in a secret store and only looked up when a run is
+//! delivered, so they never appear in the schedule itself.
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD_NO_PAD, Engine as _};
+use chrono::Utc;
+use parking_lot::RwLock;
+use parquet::basic::{ConvertedType, Repetition, Type as PhysicalType};
+use parquet::column::writer::ColumnWriter;
+use parquet::data_type::ByteArray;
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::SerializedFileWriter;
+use parquet::schema::types::Type as SchemaType;
+use reqwest::{Method, Url};
+use rustpress_core::http::HttpClient;
+use rustpress_storage::S3Presigner;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::models::reports::*;
+use crate::services::client::{ClientError, GOOGLE_TOKEN_URL};
+
+/// Google Sheets API base URL
+const SHEETS_API_BASE: &str = "https://sheets.googleapis.com/v4/spreadsheets";
+
+/// How long a presigned S3 upload URL stays valid
+const S3_UPLOAD_EXPIRY_SECS: u64 = 300;
+
+/// Connect and I/O timeout for SFTP servers
+const SFTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Secret store entry holding the credentials of the target `target_id`
+pub fn credential_id(target_id: Uuid) -> String {
+    format!("rustanalytics/delivery/{}", target_id)
+}
+
+/// Credentials of a delivery target, as named values:
+///
+/// - Google Sheets: `client_id`, `client_secret` and `refresh_token` of an
+///   OAuth grant with the spreadsheets scope
+/// - SFTP: `password`, or `private_key` with an optional `passphrase`
+/// - S3: `access_key_id` and `secret_access_key`
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Secret(HashMap<String, String>);
+
+impl Secret {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.0.insert(name.into(), value.into());
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0.get(name).map(String::as_str)
+    }
+
+    fn require(&self, name: &str) -> Result<&str, ClientError> {
+        self.get(name)
+            .ok_or_else(|| ClientError::InvalidCredentials(format!("Credentials are missing `{}`", name)))
+    }
+}
+
+impl std::fmt::Debug for Secret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut names: Vec<&String> = self.0.keys().collect();
+        names.sort();
+        f.debug_struct("Secret").field("names", &names).finish()
+    }
+}
+
+/// Where delivery credentials are kept, usually the host's secret store
+#[async_trait]
+pub trait SecretStore: Send + Sync {
+    async fn get(&self, id: &str) -> Result<Option<Secret>, ClientError>;
+
+    /// Store `secret` under `id`, replacing any previous one
+    async fn put(&self, id: &str, secret: Secret) -> Result<(), ClientError>;
+
+    /// Remove the secret under `id`, returning whether there was one
+    async fn delete(&self, id: &str) -> Result<bool, ClientError>;
+}
+
+/// Secret store kept in process memory
+#[derive(Debug, Default)]
+pub struct MemorySecretStore {
+    secrets: RwLock<HashMap<String, Secret>>,
+}
+
+impl MemorySecretStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl SecretStore for MemorySecretStore {
+    async fn get(&self, id: &str) -> Result<Option<Secret>, ClientError> {
+        Ok(self.secrets.read().get(id).cloned())
+    }
+
+    async fn put(&self, id: &str, secret: Secret) -> Result<(), ClientError> {
+        self.secrets.write().insert(id.to_string(), secret);
+        Ok(())
+    }
+
+    async fn delete(&self, id: &str) -> Result<bool, ClientError> {
+        Ok(self.secrets.write().remove(id).is_some())
+    }
+}
+
+/// Report results as a table: dimension columns followed by metric columns.
+/// Dimension cells are strings, metric cells numbers where the value parses
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReportTable {
+    pub columns: Vec<String>,
+    pub dimension_count: usize,
+    pub rows: Vec<Vec<Value>>,
+}
+
+impl ReportTable {
+    pub fn from_result(report: &CustomReport, result: &ReportResult) -> Self {
+        let columns = report
+            .dimensions
+            .iter()
+            .map(|d| d.name.clone())
+            .chain(report.metrics.iter().map(|m| m.name.clone()))
+            .collect();
+        let rows = result
+            .rows
+            .iter()
+            .map(|row| {
+                row.dimensions
+                    .iter()
+                    .map(|d| Value::String(d.clone()))
+                    .chain(row.metrics.iter().map(|m| metric_cell(&m.value)))
+                    .collect()
+            })
+            .collect();
+
+        Self {
+            columns,
+            dimension_count: report.dimensions.len(),
+            rows,
+        }
+    }
+
+    /// RFC 4180 CSV with a header row
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::new();
+        let header: Vec<String> = self.columns.iter().map(|c| csv_field(c)).collect();
+        csv.push_str(&header.join(","));
+        csv.push_str("\r\n");
+
+        for row in &self.rows {
+            let fields: Vec<String> = row.iter().map(|cell| csv_field(&cell_text(cell))).collect();
+            csv.push_str(&fields.join(","));
+            csv.push_str("\r\n");
+        }
+        csv
+    }
+
+    /// Parquet file with one row group: UTF-8 dimension columns and double
+    /// metric columns, all optional
+    pub fn to_parquet(&self) -> Result<Vec<u8>, ClientError> {
+        let fields = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                let field = if i < self.dimension_count {
+                    SchemaType::primitive_type_builder(name, PhysicalType::BYTE_ARRAY)
+                        .with_converted_type(ConvertedType::UTF8)
+                } else {
+                    SchemaType::primitive_type_builder(name, PhysicalType::DOUBLE)
+                };
+                field
+                    .with_repetition(Repetition::OPTIONAL)
+                    .build()
+                    .map(Arc::new)
+                    .map_err(parquet_error)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let schema = SchemaType::group_type_builder("report")
+            .with_fields(fields)
+            .build()
+            .map_err(parquet_error)?;
+
+        let mut data = Vec::new();
+        let mut writer = SerializedFileWriter::new(
+            &mut data,
+            Arc::new(schema),
+            Arc::new(WriterProperties::builder().build()),
+        )
+        .map_err(parquet_error)?;
+        let mut row_group = writer.next_row_group().map_err(parquet_error)?;
+        let mut column = 0;
+        while let Some(mut column_writer) = row_group.next_column().map_err(parquet_error)? {
+            let cells = self.rows.iter().map(|row| row.get(column).unwrap_or(&Value::Null));
+            match column_writer.untyped() {
+                ColumnWriter::ByteArrayColumnWriter(w) => {
+                    let (values, levels): (Vec<_>, Vec<_>) = cells
+                        .map(|cell| match cell {
+                            Value::Null => (None, 0),
+                            cell => (Some(ByteArray::from(cell_text(cell).as_str())), 1),
+                        })
+                        .unzip();
+                    let values: Vec<ByteArray> = values.into_iter().flatten().collect();
+                    w.write_batch(&values, Some(&levels), None).map_err(parquet_error)?;
+                }
+                ColumnWriter::DoubleColumnWriter(w) => {
+                    let (values, levels): (Vec<_>, Vec<_>) = cells
+                        .map(|cell| match cell.as_f64() {
+                            Some(value) => (Some(value), 1),
+                            None => (None, 0),
+                        })
+                        .unzip();
+                    let values: Vec<f64> = values.into_iter().flatten().collect();
+                    w.write_batch(&values, Some(&levels), None).map_err(parquet_error)?;
+                }
+                _ => unreachable!("report columns are strings or doubles"),
+            }
+            column_writer.close().map_err(parquet_error)?;
+            column += 1;
+        }
+        row_group.close().map_err(parquet_error)?;
+        writer.close().map_err(parquet_error)?;
+
+        Ok(data)
+    }
+}
+
+/// Metric value as a number when it is one or parses as one
+fn metric_cell(value: &Value) -> Value {
+    match value {
+        Value::String(s) => s
+            .parse::<i64>()
+            .map(Value::from)
+            .ok()
+            .or_else(|| s.parse::<f64>().ok().and_then(|f| serde_json::Number::from_f64(f).map(Value::Number)))
+            .unwrap_or_else(|| value.clone()),
+        _ => value.clone(),
+    }
+}
+
+fn cell_text(cell: &Value) -> String {
+    match cell {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn parquet_error(e: parquet::errors::ParquetError) -> ClientError {
+    ClientError::DeliveryFailed(format!("Failed to write Parquet: {}", e))
+}
+
+/// Results rendered for one target
+#[derive(Debug, Clone)]
+pub struct DeliveryFile {
+    pub file_name: String,
+    pub content_type: String,
+    pub data: Vec<u8>,
+    /// The rows the file was rendered from, for targets taking cell values
+    pub table: ReportTable,
+}
+
+impl DeliveryFile {
+    pub fn render(
+        report: &CustomReport,
+        result: &ReportResult,
+        format: DeliveryFormat,
+    ) -> Result<Self, ClientError> {
+        let table = ReportTable::from_result(report, result);
+        let data = match format {
+            DeliveryFormat::Csv => table.to_csv().into_bytes(),
+            DeliveryFormat::Parquet => table.to_parquet()?,
+        };
+        let file_name = format!(
+            "{}_{}.{}",
+            report.name.replace(' ', "_").to_lowercase(),
+            result.generated_at.format("%Y%m%d_%H%M%S"),
+            format.extension()
+        );
+
+        Ok(Self {
+            file_name,
+            content_type: format.content_type().to_string(),
+            data,
+            table,
+        })
+    }
+}
+
+/// Sends rendered results to one kind of destination
+#[async_trait]
+pub trait DeliveryTransport: Send + Sync {
+    /// Send `file` to `destination`, returning where it landed
+    async fn send(
+        &self,
+        destination: &DeliveryDestination,
+        file: &DeliveryFile,
+        secret: &Secret,
+    ) -> Result<String, ClientError>;
+}
+
+/// Writes results into a Google Sheet through the Sheets API
+pub struct GoogleSheetsTransport {
+    http: HttpClient,
+}
+
+impl GoogleSheetsTransport {
+    pub fn new(http: HttpClient) -> Self {
+        Self { http }
+    }
+
+    /// Access token for the OAuth grant in `secret`
+    async fn access_token(&self, secret: &Secret) -> Result<String, ClientError> {
+        #[derive(Deserialize)]
+        struct TokenResponse {
+            access_token: String,
+        }
+
+        let request = self.http.post(GOOGLE_TOKEN_URL).form(&[
+            ("grant_type", "refresh_token"),
+            ("client_id", secret.require("client_id")?),
+            ("client_secret", secret.require("client_secret")?),
+            ("refresh_token", secret.require("refresh_token")?),
+        ]);
+        let response = self
+            .http
+            .send(request)
+            .await
+            .map_err(|e| ClientError::RequestFailed(e.to_string()))?;
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(ClientError::AuthenticationFailed(error_text));
+        }
+
+        let token: TokenResponse = response.json().await?;
+        Ok(token.access_token)
+    }
+
+    async fn call(
+        &self,
+        method: Method,
+        url: Url,
+        token: &str,
+        body: Option<Value>,
+    ) -> Result<(), ClientError> {
+        let mut request = self.http.request(method, url).bearer_auth(token);
+        if let Some(body) = body {
+            request = request.json(&body);
+        }
+        let response = self
+            .http
+            .send(request)
+            .await
+            .map_err(|e| ClientError::RequestFailed(e.to_string()))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(ClientError::DeliveryFailed(format!(
+                "Sheets API returned {}: {}",
+                status, error_text
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Sheets API URL for `range` of a spreadsheet, followed by `action`
+fn sheets_url(spreadsheet_id: &str, range: &str, action: &str) -> Result<Url, ClientError> {
+    let mut url = Url::parse(SHEETS_API_BASE).expect("valid Sheets API URL");
+    url.path_segments_mut()
+        .map_err(|_| ClientError::InvalidDeliveryTarget("Invalid spreadsheet".to_string()))?
+        .extend([spreadsheet_id, "values", &format!("{}{}", range, action)]);
+    Ok(url)
+}
+
+#[async_trait]
+impl DeliveryTransport for GoogleSheetsTransport {
+    async fn send(
+        &self,
+        destination: &DeliveryDestination,
+        file: &DeliveryFile,
+        secret: &Secret,
+    ) -> Result<String, ClientError> {
+        let DeliveryDestination::GoogleSheet { spreadsheet_id, sheet_name, append } = destination else {
+            return Err(ClientError::InvalidDeliveryTarget("Not a Google Sheet".to_string()));
+        };
+
+        let token = self.access_token(secret).await?;
+        let range = format!("'{}'", sheet_name.replace('\'', "''"));
+        let rows = file.table.rows.iter().cloned().map(Value::Array);
+
+        if *append {
+            let mut url = sheets_url(spreadsheet_id, &range, ":append")?;
+            url.query_pairs_mut()
+                .append_pair("valueInputOption", "RAW")
+                .append_pair("insertDataOption", "INSERT_ROWS");
+            let values: Vec<Value> = rows.collect();
+            self.call(Method::POST, url, &token, Some(json!({ "values": values })))
+                .await?;
+        } else {
+            let clear = sheets_url(spreadsheet_id, &range, ":clear")?;
+            self.call(Method::POST, clear, &token, Some(json!({}))).await?;
+
+            let mut url = sheets_url(spreadsheet_id, &range, "")?;
+            url.query_pairs_mut().append_pair("valueInputOption", "RAW");
+            let header = Value::Array(file.table.columns.iter().cloned().map(Value::String).collect());
+            let values: Vec<Value> = std::iter::once(header).chain(rows).collect();
+            self.call(Method::PUT, url, &token, Some(json!({ "range": range, "values": values })))
+                .await?;
+        }
+
+        Ok(format!("https://docs.google.com/spreadsheets/d/{}", spreadsheet_id))
+    }
+}
+
+/// Uploads results to an S3-compatible bucket with a presigned PUT
+pub struct S3Transport {
+    http: HttpClient,
+}
+
+impl S3Transport {
+    pub fn new(http: HttpClient) -> Self {
+        Self { http }
+    }
+}
+
+#[async_trait]
+impl DeliveryTransport for S3Transport {
+    async fn send(
+        &self,
+        destination: &DeliveryDestination,
+        file: &DeliveryFile,
+        secret: &Secret,
+    ) -> Result<String, ClientError> {
+        let DeliveryDestination::S3 { bucket_url, region, prefix } = destination else {
+            return Err(ClientError::InvalidDeliveryTarget("Not an S3 bucket".to_string()));
+        };
+
+        let key = object_key(prefix, &file.file_name);
+        let presigner = S3Presigner::new(
+            bucket_url,
+            region.as_str(),
+            secret.require("access_key_id")?,
+            secret.require("secret_access_key")?,
+        )
+        .map_err(|e| ClientError::InvalidDeliveryTarget(e.to_string()))?;
+        let presigned = presigner
+            .presign("PUT", &key, &[], S3_UPLOAD_EXPIRY_SECS)
+            .map_err(|e| ClientError::DeliveryFailed(e.to_string()))?;
+
+        let request = self
+            .http
+            .request(Method::PUT, presigned.url.as_str())
+            .header(reqwest::header::CONTENT_TYPE, &file.content_type)
+            .body(file.data.clone());
+        let response = self
+            .http
+            .send(request)
+            .await
+            .map_err(|e| ClientError::RequestFailed(e.to_string()))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(ClientError::DeliveryFailed(format!(
+                "S3 returned {}: {}",
+                status, error_text
+            )));
+        }
+
+        Ok(format!("{}/{}", bucket_url.trim_end_matches('/'), key))
+    }
+}
+
+fn object_key(prefix: &str, file_name: &str) -> String {
+    let prefix = prefix.trim_matches('/');
+    if prefix.is_empty() {
+        file_name.to_string()
+    } else {
+        format!("{}/{}", prefix, file_name)
+    }
+}
+
+/// Drops results in a directory on an SFTP server. The file is written
+/// under a temporary name and renamed once complete, so pickup jobs never
+/// see a partial file
+#[derive(Debug, Default)]
+pub struct SftpTransport;
+
+impl SftpTransport {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl DeliveryTransport for SftpTransport {
+    async fn send(
+        &self,
+        destination: &DeliveryDestination,
+        file: &DeliveryFile,
+        secret: &Secret,
+    ) -> Result<String, ClientError> {
+        let DeliveryDestination::Sftp { host, port, username, directory, host_key_fingerprint } =
+            destination.clone()
+        else {
+            return Err(ClientError::InvalidDeliveryTarget("Not an SFTP server".to_string()));
+        };
+        let file_name = file.file_name.clone();
+        let data = file.data.clone();
+        let secret = secret.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let addr = (host.as_str(), port)
+                .to_socket_addrs()
+                .map_err(|e| ClientError::DeliveryFailed(format!("Cannot resolve {}: {}", host, e)))?
+                .next()
+                .ok_or_else(|| ClientError::DeliveryFailed(format!("Cannot resolve {}", host)))?;
+            let tcp = TcpStream::connect_timeout(&addr, SFTP_TIMEOUT)
+                .map_err(|e| ClientError::DeliveryFailed(format!("Cannot connect to {}: {}", host, e)))?;
+
+            let mut session = ssh2::Session::new().map_err(sftp_error)?;
+            session.set_tcp_stream(tcp);
+            session.set_timeout(SFTP_TIMEOUT.as_millis() as u32);
+            session.handshake().map_err(sftp_error)?;
+
+            let fingerprint = session
+                .host_key_hash(ssh2::HashType::Sha256)
+                .map(|hash| STANDARD_NO_PAD.encode(hash))
+                .unwrap_or_default();
+            let expected = host_key_fingerprint.trim();
+            if fingerprint != expected.strip_prefix("SHA256:").unwrap_or(expected) {
+                return Err(ClientError::DeliveryFailed(format!(
+                    "Host key of {} does not match the pinned fingerprint (got SHA256:{})",
+                    host, fingerprint
+                )));
+            }
+
+            match secret.get("private_key") {
+                Some(key) => session
+                    .userauth_pubkey_memory(&username, None, key, secret.get("passphrase"))
+                    .map_err(|e| ClientError::AuthenticationFailed(e.to_string()))?,
+                None => session
+                    .userauth_password(&username, secret.require("password")?)
+                    .map_err(|e| ClientError::AuthenticationFailed(e.to_string()))?,
+            }
+
+            let sftp = session.sftp().map_err(sftp_error)?;
+            let path = Path::new(&directory).join(&file_name);
+            let partial = Path::new(&directory).join(format!(".{}.part", file_name));
+            let mut remote = sftp.create(&partial).map_err(sftp_error)?;
+            remote
+                .write_all(&data)
+                .map_err(|e| ClientError::DeliveryFailed(e.to_string()))?;
+            drop(remote);
+            sftp.rename(&partial, &path, Some(ssh2::RenameFlags::OVERWRITE | ssh2::RenameFlags::ATOMIC))
+                .map_err(sftp_error)?;
+
+            Ok(format!("sftp://{}:{}{}", host, port, path.display()))
+        })
+        .await
+        .map_err(|e| ClientError::DeliveryFailed(e.to_string()))?
+    }
+}
+
+fn sftp_error(e: ssh2::Error) -> ClientError {
+    ClientError::DeliveryFailed(format!("SFTP: {}", e))
+}
+
+/// Check a target can be delivered to before it is scheduled
+pub fn validate_target(target: &DeliveryTarget) -> Result<(), ClientError> {
+    let invalid = |message: &str| Err(ClientError::InvalidDeliveryTarget(format!("{}: {}", target.name, message)));
+
+    match &target.destination {
+        DeliveryDestination::GoogleSheet { spreadsheet_id, sheet_name, .. } => {
+            if spreadsheet_id.is_empty() || sheet_name.is_empty() {
+                return invalid("spreadsheet and sheet are required");
+            }
+            if target.format != DeliveryFormat::Csv {
+                return invalid("Google Sheets only take tabular (CSV) results");
+            }
+        }
+        DeliveryDestination::Sftp { host, username, directory, host_key_fingerprint, .. } => {
+            if host.is_empty() || username.is_empty() {
+                return invalid("host and username are required");
+            }
+            if !directory.starts_with('/') {
+                return invalid("the directory must be an absolute path");
+            }
+            if host_key_fingerprint.trim().is_empty() {
+                return invalid("the server's host key fingerprint is required");
+            }
+        }
+        DeliveryDestination::S3 { bucket_url, region, .. } => {
+            if Url::parse(bucket_url).map(|url| url.host_str().is_none()).unwrap_or(true) {
+                return invalid("the bucket URL is invalid");
+            }
+            if region.is_empty() {
+                return invalid("region is required");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Delivers scheduled report results to their targets
+pub struct ReportDelivery {
+    secrets: Arc<dyn SecretStore>,
+    transports: HashMap<DeliveryKind, Arc<dyn DeliveryTransport>>,
+}
+
+impl ReportDelivery {
+    /// Delivery with the built-in Sheets, SFTP and S3 transports, sending
+    /// HTTP requests through `http`
+    pub fn new(http: HttpClient, secrets: Arc<dyn SecretStore>) -> Self {
+        let mut transports: HashMap<DeliveryKind, Arc<dyn DeliveryTransport>> = HashMap::new();
+        transports.insert(DeliveryKind::GoogleSheet, Arc::new(GoogleSheetsTransport::new(http.clone())));
+        transports.insert(DeliveryKind::Sftp, Arc::new(SftpTransport::new()));
+        transports.insert(DeliveryKind::S3, Arc::new(S3Transport::new(http)));
+
+        Self { secrets, transports }
+    }
+
+    /// Send results for `kind` destinations through `transport`
+    pub fn with_transport(mut self, kind: DeliveryKind, transport: Arc<dyn DeliveryTransport>) -> Self {
+        self.transports.insert(kind, transport);
+        self
+    }
+
+    pub fn secrets(&self) -> &Arc<dyn SecretStore> {
+        &self.secrets
+    }
+
+    /// Deliver one run's results to each of `targets`, returning the
+    /// outcome per target. A failing target doesn't stop the others
+    pub async fn deliver(
+        &self,
+        report: &CustomReport,
+        result: &ReportResult,
+        targets: &[DeliveryTarget],
+    ) -> Vec<DeliveryStatus> {
+        let mut statuses = Vec::with_capacity(targets.len());
+
+        for target in targets {
+            let attempted_at = Utc::now();
+            let status = if !target.enabled {
+                DeliveryStatus {
+                    target_id: target.id,
+                    state: DeliveryState::Skipped,
+                    location: None,
+                    size_bytes: 0,
+                    attempted_at,
+                    error: None,
+                }
+            } else {
+                match self.deliver_to(report, result, target).await {
+                    Ok((location, size_bytes)) => {
+                        info!("Delivered report {} to {}", report.id, location);
+                        DeliveryStatus {
+                            target_id: target.id,
+                            state: DeliveryState::Delivered,
+                            location: Some(location),
+                            size_bytes,
+                            attempted_at,
+                            error: None,
+                        }
+                    }
+                    Err(e) => {
+                        warn!("Failed to deliver report {} to target {}: {}", report.id, target.name, e);
+                        DeliveryStatus {
+                            target_id: target.id,
+                            state: DeliveryState::Failed,
+                            location: None,
+                            size_bytes: 0,
+                            attempted_at,
+                            error: Some(e.to_string()),
+                        }
+                    }
+                }
+            };
+            statuses.push(status);
+        }
+
+        statuses
+    }
+
+    async fn deliver_to(
+        &self,
+        report: &CustomReport,
+        result: &ReportResult,
+        target: &DeliveryTarget,
+    ) -> Result<(String, u64), ClientError> {
+        validate_target(target)?;
+        let transport = self
+            .transports
+            .get(&target.destination.kind())
+            .ok_or_else(|| ClientError::InvalidDeliveryTarget(format!("{}: no transport", target.name)))?;
+
+        let credential_id = target
+            .credential_id
+            .as_deref()
+            .ok_or_else(|| ClientError::InvalidCredentials(format!("{} has no credentials", target.name)))?;
+        let secret = self.secrets.get(credential_id).await?.ok_or_else(|| {
+            ClientError::InvalidCredentials(format!("Credentials for {} are not in the secret store", target.name))
+        })?;
+
+        let file = DeliveryFile::render(report, result, target.format)?;
+        let location = transport.send(&target.destination, &file, &secret).await?;
+        Ok((location, file.data.len() as u64))
+    }
+}
+
+impl std::fmt::Debug for ReportDelivery {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ReportDelivery")
+            .field("transports", &self.transports.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
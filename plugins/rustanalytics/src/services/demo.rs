@@ -0,0 +1,1398 @@
+//! Demo Data
+//!
+//! Synthetic Google Analytics data for demos and UI development. A client
+//! in demo mode answers every report from a seeded generator instead of
+//! the GA4 API, so dashboards work without a property or credentials:
+//!
+//! - the same seed always gives the same numbers for the same request
+//! - traffic follows weekly and daily cycles with a slow upward trend, and
+//!   days after today have none
+//! - dimensions come from linked tables, so a city always lies in its
+//!   country and a source keeps its medium, and breakdowns add up to the
+//!   totals
+//! - date ranges, filters, ordering, paging and metric aggregations are
+//!   applied the way GA applies them; regular expression filters match
+//!   as substrings
+//!
+//! Responses are tagged with [`DEMO_REPORT_KIND`] so reports built from
+//! them are flagged as synthetic.
+
+use std::collections::{BTreeMap, HashMap};
+use std::f64::consts::PI;
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Timelike, Utc};
+
+use crate::models::api::*;
+use crate::services::client::ClientError;
+
+/// Property id of demo clients
+pub const DEMO_PROPERTY_ID: &str = "demo";
+
+/// Seed used when the settings don't choose one
+pub const DEFAULT_DEMO_SEED: u64 = 20_240_101;
+
+/// `kind` of every demo response
+pub const DEMO_REPORT_KIND: &str = "rustanalytics#demoReport";
+
+/// Rows per report when the request sets no limit, as GA
+const DEFAULT_LIMIT: usize = 10_000;
+
+/// Most dimension value combinations generated; the rest are dropped and
+/// reported as data loss, like GA's "(other)" row
+const MAX_COMBINATIONS: usize = 5_000;
+
+/// Longest date range generated day by day
+const MAX_DAYS: i64 = 5 * 366;
+
+/// Traffic is generated relative to this day
+const EPOCH: (i32, u32, u32) = (2024, 1, 1);
+
+/// Share of the day's sessions in each hour, midnight first
+const HOURLY: [f64; 24] = [
+    1.2, 0.8, 0.6, 0.5, 0.5, 0.7, 1.5, 2.8, 4.6, 6.0, 6.6, 6.8,
+    6.5, 6.6, 6.7, 6.4, 6.0, 5.4, 4.9, 4.7, 4.6, 4.0, 3.0, 2.1,
+];
+
+/// Traffic of each weekday relative to the average, Monday first
+const WEEKDAYS: [f64; 7] = [1.06, 1.09, 1.1, 1.07, 0.99, 0.72, 0.66];
+
+const WEEKDAY_NAMES: [&str; 7] = [
+    "Sunday", "Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday",
+];
+
+/// Dimensions that split time rather than traffic
+const TIME_DIMENSIONS: &[&str] = &[
+    "date", "dateHour", "hour", "day", "dayOfWeek", "dayOfWeekName", "month", "year",
+    "yearMonth", "minutesAgo",
+];
+
+/// Dimension the rows of multi-range reports are tagged with
+const DATE_RANGE_DIMENSION: &str = "dateRange";
+
+/// Dimensions answered with the values of another
+const ALIASES: &[(&str, &str)] = &[
+    ("source", "sessionSource"),
+    ("medium", "sessionMedium"),
+    ("sourceMedium", "sessionSourceMedium"),
+    ("defaultChannelGroup", "sessionDefaultChannelGroup"),
+    ("firstUserSource", "sessionSource"),
+    ("firstUserMedium", "sessionMedium"),
+    ("firstUserDefaultChannelGroup", "sessionDefaultChannelGroup"),
+    ("campaignName", "sessionCampaignName"),
+    ("unifiedPagePathScreen", "pagePath"),
+    ("pagePathPlusQueryString", "pagePath"),
+    ("landingPage", "pagePath"),
+    ("landingPagePlusQueryString", "pagePath"),
+    ("unifiedScreenName", "pageTitle"),
+    ("unifiedScreenClass", "pageTitle"),
+    ("deviceModel", "mobileDeviceModel"),
+];
+
+/// Related dimensions whose values always appear together
+struct Table {
+    columns: &'static [&'static str],
+    rows: &'static [(&'static [&'static str], f64)],
+}
+
+const TABLES: &[Table] = &[
+    Table {
+        columns: &["country", "countryId", "city"],
+        rows: &[
+            (&["United States", "US", "New York"], 9.0),
+            (&["United States", "US", "San Francisco"], 6.0),
+            (&["United States", "US", "Chicago"], 4.0),
+            (&["United States", "US", "Austin"], 3.0),
+            (&["United Kingdom", "GB", "London"], 7.0),
+            (&["United Kingdom", "GB", "Manchester"], 2.0),
+            (&["Germany", "DE", "Berlin"], 4.0),
+            (&["Germany", "DE", "Munich"], 2.0),
+            (&["India", "IN", "Bengaluru"], 5.0),
+            (&["India", "IN", "Mumbai"], 3.0),
+            (&["Canada", "CA", "Toronto"], 3.0),
+            (&["France", "FR", "Paris"], 3.0),
+            (&["Australia", "AU", "Sydney"], 2.0),
+            (&["Brazil", "BR", "São Paulo"], 2.0),
+            (&["Japan", "JP", "Tokyo"], 2.0),
+            (&["Netherlands", "NL", "Amsterdam"], 1.5),
+        ],
+    },
+    Table {
+        columns: &[
+            "sessionSource",
+            "sessionMedium",
+            "sessionSourceMedium",
+            "sessionDefaultChannelGroup",
+        ],
+        rows: &[
+            (&["google", "organic", "google / organic", "Organic Search"], 38.0),
+            (&["(direct)", "(none)", "(direct) / (none)", "Direct"], 22.0),
+            (&["google", "cpc", "google / cpc", "Paid Search"], 6.0),
+            (&["newsletter", "email", "newsletter / email", "Email"], 5.0),
+            (&["bing", "organic", "bing / organic", "Organic Search"], 4.0),
+            (&["facebook.com", "referral", "facebook.com / referral", "Organic Social"], 4.0),
+            (&["t.co", "referral", "t.co / referral", "Organic Social"], 3.0),
+            (&["news.ycombinator.com", "referral", "news.ycombinator.com / referral", "Referral"], 3.0),
+            (&["duckduckgo", "organic", "duckduckgo / organic", "Organic Search"], 2.0),
+            (&["linkedin.com", "referral", "linkedin.com / referral", "Organic Social"], 2.0),
+            (&["github.com", "referral", "github.com / referral", "Referral"], 2.0),
+        ],
+    },
+    Table {
+        columns: &["sessionCampaignName"],
+        rows: &[
+            (&["(not set)"], 70.0),
+            (&["spring_sale"], 10.0),
+            (&["newsletter_weekly"], 9.0),
+            (&["product_launch"], 7.0),
+            (&["retargeting"], 4.0),
+        ],
+    },
+    Table {
+        columns: &["deviceCategory", "operatingSystem", "browser", "platform", "mobileDeviceModel"],
+        rows: &[
+            (&["desktop", "Windows", "Chrome", "web", "(not set)"], 22.0),
+            (&["desktop", "Macintosh", "Chrome", "web", "(not set)"], 12.0),
+            (&["desktop", "Macintosh", "Safari", "web", "(not set)"], 6.0),
+            (&["desktop", "Windows", "Edge", "web", "(not set)"], 6.0),
+            (&["desktop", "Windows", "Firefox", "web", "(not set)"], 3.0),
+            (&["desktop", "Linux", "Firefox", "web", "(not set)"], 3.0),
+            (&["mobile", "iOS", "Safari", "web", "iPhone"], 20.0),
+            (&["mobile", "Android", "Chrome", "web", "Pixel 8"], 9.0),
+            (&["mobile", "Android", "Chrome", "web", "Galaxy S24"], 9.0),
+            (&["mobile", "iOS", "Chrome", "web", "iPhone"], 3.0),
+            (&["tablet", "iOS", "Safari", "web", "iPad"], 4.0),
+            (&["tablet", "Android", "Chrome", "web", "Galaxy Tab S9"], 1.5),
+        ],
+    },
+    Table {
+        columns: &["pagePath", "pageTitle", "contentGroup"],
+        rows: &[
+            (&["/", "Home", "Home"], 20.0),
+            (&["/blog/", "Blog", "Blog"], 8.0),
+            (&["/blog/getting-started-with-rust/", "Getting Started with Rust", "Blog"], 7.0),
+            (&["/blog/async-rust-in-practice/", "Async Rust in Practice", "Blog"], 5.0),
+            (&["/blog/building-a-cms/", "Building a CMS in Rust", "Blog"], 4.0),
+            (&["/pricing/", "Pricing", "Product"], 6.0),
+            (&["/features/", "Features", "Product"], 5.0),
+            (&["/docs/", "Documentation", "Docs"], 6.0),
+            (&["/docs/installation/", "Installation", "Docs"], 4.0),
+            (&["/shop/", "Shop", "Shop"], 4.0),
+            (&["/shop/ferris-plush/", "Ferris Plush", "Shop"], 2.0),
+            (&["/about/", "About Us", "Company"], 3.0),
+            (&["/contact/", "Contact", "Company"], 2.0),
+            (&["/checkout/", "Checkout", "Shop"], 1.5),
+            (&["/search/", "Search Results", "Search"], 1.0),
+        ],
+    },
+    Table {
+        columns: &["eventName"],
+        rows: &[
+            (&["page_view"], 40.0),
+            (&["user_engagement"], 22.0),
+            (&["scroll"], 14.0),
+            (&["session_start"], 12.0),
+            (&["first_visit"], 6.0),
+            (&["click"], 4.0),
+            (&["view_item"], 0.8),
+            (&["form_submit"], 0.8),
+            (&["file_download"], 0.4),
+            (&["add_to_cart"], 0.3),
+            (&["begin_checkout"], 0.15),
+            (&["purchase"], 0.08),
+        ],
+    },
+    Table {
+        columns: &["itemName", "itemCategory", "itemBrand"],
+        rows: &[
+            (&["RustPress T-Shirt", "Apparel", "RustPress"], 30.0),
+            (&["Ferris Plush", "Toys", "Ferris & Co"], 25.0),
+            (&["RustPress Hoodie", "Apparel", "RustPress"], 18.0),
+            (&["Sticker Pack", "Accessories", "RustPress"], 15.0),
+            (&["Crab Mug", "Accessories", "Ferris & Co"], 12.0),
+        ],
+    },
+    Table {
+        columns: &["searchTerm"],
+        rows: &[
+            (&["installation"], 20.0),
+            (&["themes"], 15.0),
+            (&["plugins"], 14.0),
+            (&["pricing"], 10.0),
+            (&["api"], 9.0),
+            (&["migration from wordpress"], 7.0),
+        ],
+    },
+    Table {
+        columns: &["userAgeBracket"],
+        rows: &[
+            (&["18-24"], 14.0),
+            (&["25-34"], 32.0),
+            (&["35-44"], 24.0),
+            (&["45-54"], 15.0),
+            (&["55-64"], 9.0),
+            (&["65+"], 6.0),
+        ],
+    },
+    Table {
+        columns: &["userGender"],
+        rows: &[(&["male"], 54.0), (&["female"], 44.0), (&["unknown"], 2.0)],
+    },
+    Table {
+        columns: &["language", "languageCode"],
+        rows: &[
+            (&["English", "en-us"], 62.0),
+            (&["German", "de"], 8.0),
+            (&["Spanish", "es"], 7.0),
+            (&["French", "fr"], 6.0),
+            (&["Portuguese", "pt-br"], 5.0),
+            (&["Japanese", "ja"], 4.0),
+            (&["Hindi", "hi"], 4.0),
+            (&["Dutch", "nl"], 2.0),
+        ],
+    },
+    Table {
+        columns: &["newVsReturning"],
+        rows: &[(&["new"], 62.0), (&["returning"], 38.0)],
+    },
+];
+
+/// Metrics the generator knows, with their type
+const METRICS: &[(&str, MetricType)] = &[
+    ("sessions", MetricType::TypeInteger),
+    ("totalUsers", MetricType::TypeInteger),
+    ("activeUsers", MetricType::TypeInteger),
+    ("newUsers", MetricType::TypeInteger),
+    ("screenPageViews", MetricType::TypeInteger),
+    ("screenPageViewsPerSession", MetricType::TypeFloat),
+    ("screenPageViewsPerUser", MetricType::TypeFloat),
+    ("averageSessionDuration", MetricType::TypeSeconds),
+    ("userEngagementDuration", MetricType::TypeSeconds),
+    ("engagedSessions", MetricType::TypeInteger),
+    ("engagementRate", MetricType::TypeFloat),
+    ("bounceRate", MetricType::TypeFloat),
+    ("sessionsPerUser", MetricType::TypeFloat),
+    ("eventCount", MetricType::TypeInteger),
+    ("eventsPerSession", MetricType::TypeFloat),
+    ("conversions", MetricType::TypeInteger),
+    ("keyEvents", MetricType::TypeInteger),
+    ("sessionConversionRate", MetricType::TypeFloat),
+    ("ecommercePurchases", MetricType::TypeInteger),
+    ("transactions", MetricType::TypeInteger),
+    ("totalRevenue", MetricType::TypeCurrency),
+    ("purchaseRevenue", MetricType::TypeCurrency),
+    ("averagePurchaseRevenue", MetricType::TypeCurrency),
+];
+
+/// Where the values of a dimension come from
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Source {
+    Time,
+    Range,
+    Table(usize, usize),
+    /// Dimension the generator doesn't know; gets a few numbered values
+    Synthetic,
+}
+
+fn source(name: &str) -> Source {
+    if name == DATE_RANGE_DIMENSION {
+        return Source::Range;
+    }
+    if TIME_DIMENSIONS.contains(&name) {
+        return Source::Time;
+    }
+    let name = ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == name)
+        .map_or(name, |(_, canonical)| canonical);
+    TABLES
+        .iter()
+        .enumerate()
+        .find_map(|(t, table)| {
+            table.columns.iter().position(|c| *c == name).map(|c| Source::Table(t, c))
+        })
+        .unwrap_or(Source::Synthetic)
+}
+
+/// Additive measures of a slice of traffic
+#[derive(Debug, Clone, Copy, Default)]
+struct Totals {
+    sessions: f64,
+    users: f64,
+    new_users: f64,
+    views: f64,
+    engaged: f64,
+    duration: f64,
+    events: f64,
+    conversions: f64,
+    purchases: f64,
+    revenue: f64,
+}
+
+impl Totals {
+    fn add(&mut self, other: &Totals) {
+        self.sessions += other.sessions;
+        self.users += other.users;
+        self.new_users += other.new_users;
+        self.views += other.views;
+        self.engaged += other.engaged;
+        self.duration += other.duration;
+        self.events += other.events;
+        self.conversions += other.conversions;
+        self.purchases += other.purchases;
+        self.revenue += other.revenue;
+    }
+
+    fn is_empty(&self) -> bool {
+        self.sessions < 0.5
+    }
+}
+
+/// How a slice of traffic behaves per session
+#[derive(Debug, Clone, Copy)]
+struct Profile {
+    users_per_session: f64,
+    new_user_share: f64,
+    pages_per_session: f64,
+    seconds_per_session: f64,
+    engagement: f64,
+    events_per_view: f64,
+    conversion_rate: f64,
+    purchase_rate: f64,
+    order_value: f64,
+}
+
+impl Profile {
+    fn totals(&self, sessions: f64) -> Totals {
+        let users = sessions * self.users_per_session;
+        let views = sessions * self.pages_per_session;
+        let purchases = sessions * self.purchase_rate;
+        Totals {
+            sessions,
+            users,
+            new_users: users * self.new_user_share,
+            views,
+            engaged: sessions * self.engagement,
+            duration: sessions * self.seconds_per_session,
+            events: views * self.events_per_view,
+            conversions: sessions * self.conversion_rate,
+            purchases,
+            revenue: purchases * self.order_value,
+        }
+    }
+}
+
+/// Slice of time traffic is generated for
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    range: usize,
+    date: Option<NaiveDate>,
+    hour: Option<u32>,
+    minutes_ago: Option<u32>,
+    sessions: f64,
+}
+
+impl Bucket {
+    fn time_value(&self, dimension: &str) -> String {
+        let date = self.date;
+        match dimension {
+            "date" => date.map(|d| d.format("%Y%m%d").to_string()),
+            "dateHour" => date.map(|d| format!("{}{:02}", d.format("%Y%m%d"), self.hour.unwrap_or(0))),
+            "hour" => self.hour.map(|h| format!("{:02}", h)),
+            "day" => date.map(|d| format!("{:02}", d.day())),
+            "dayOfWeek" => date.map(|d| d.weekday().num_days_from_sunday().to_string()),
+            "dayOfWeekName" => {
+                date.map(|d| WEEKDAY_NAMES[d.weekday().num_days_from_sunday() as usize].to_string())
+            }
+            "month" => date.map(|d| format!("{:02}", d.month())),
+            "year" => date.map(|d| d.year().to_string()),
+            "yearMonth" => date.map(|d| d.format("%Y%m").to_string()),
+            "minutesAgo" => self.minutes_ago.map(|m| format!("{:02}", m)),
+            _ => None,
+        }
+        .unwrap_or_else(|| "(not set)".to_string())
+    }
+}
+
+/// Dimension names with their values
+type Values = Vec<(String, String)>;
+
+/// Picks an aggregate of a metric from the metric values of the rows, the
+/// metric's index and the rows' totals
+type Pick<'a> = dyn Fn(&[&Vec<f64>], usize, &Totals) -> f64 + 'a;
+
+/// Dimension value combination with its share of the traffic
+struct Combination {
+    values: HashMap<String, String>,
+    weight: f64,
+    profile: Profile,
+}
+
+/// What to generate and how to shape it
+struct Query<'a> {
+    dimensions: Vec<String>,
+    metrics: Vec<String>,
+    ranges: Vec<String>,
+    buckets: Vec<Bucket>,
+    dimension_filter: Option<&'a FilterExpression>,
+    metric_filter: Option<&'a FilterExpression>,
+    order_bys: &'a [OrderBy],
+    aggregations: &'a [String],
+    keep_empty_rows: bool,
+    offset: usize,
+    limit: usize,
+}
+
+/// Generated report, in the shape of GA responses
+struct Output {
+    dimension_headers: Vec<DimensionHeader>,
+    metric_headers: Vec<MetricHeader>,
+    rows: Vec<Row>,
+    totals: Option<Vec<Row>>,
+    maximums: Option<Vec<Row>>,
+    minimums: Option<Vec<Row>>,
+    row_count: usize,
+    data_loss: bool,
+}
+
+/// Seeded generator of Google Analytics responses
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DemoData {
+    seed: u64,
+}
+
+impl DemoData {
+    /// Generator producing the data of `seed`
+    pub fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Answer a report request
+    pub fn run_report(&self, request: &RunReportRequest) -> Result<RunReportResponse, ClientError> {
+        self.run_report_at(request, Utc::now())
+    }
+
+    /// Answer a report request as if it were sent at `now`
+    pub fn run_report_at(
+        &self,
+        request: &RunReportRequest,
+        now: DateTime<Utc>,
+    ) -> Result<RunReportResponse, ClientError> {
+        let query = self.report_query(
+            &request.date_ranges,
+            request.dimensions.as_deref().unwrap_or_default(),
+            &request.metrics,
+            now,
+        )?;
+        let output = self.generate(Query {
+            dimension_filter: request.dimension_filter.as_ref(),
+            metric_filter: request.metric_filter.as_ref(),
+            order_bys: request.order_bys.as_deref().unwrap_or_default(),
+            aggregations: request.metric_aggregations.as_deref().unwrap_or_default(),
+            keep_empty_rows: request.keep_empty_rows.unwrap_or(false),
+            offset: request.offset.unwrap_or(0).max(0) as usize,
+            limit: limit(request.limit),
+            ..query
+        });
+
+        Ok(RunReportResponse {
+            dimension_headers: Some(output.dimension_headers),
+            metric_headers: Some(output.metric_headers),
+            rows: Some(output.rows),
+            totals: output.totals,
+            maximums: output.maximums,
+            minimums: output.minimums,
+            row_count: Some(output.row_count as i32),
+            metadata: Some(ResponseMetaData {
+                data_loss_from_other_row: Some(output.data_loss),
+                schema_restriction_response: None,
+                currency_code: Some("USD".to_string()),
+                time_zone: Some("Etc/UTC".to_string()),
+                subject_to_thresholding: Some(false),
+                sampling_metadatas: None,
+                privacy_redactions: None,
+            }),
+            property_quota: request.return_property_quota.unwrap_or(false).then(quota),
+            kind: Some(DEMO_REPORT_KIND.to_string()),
+        })
+    }
+
+    /// Answer a realtime report request
+    pub fn run_realtime_report(
+        &self,
+        request: &RunRealtimeReportRequest,
+    ) -> Result<RunRealtimeReportResponse, ClientError> {
+        self.run_realtime_report_at(request, Utc::now())
+    }
+
+    /// Answer a realtime report request as if it were sent at `now`
+    pub fn run_realtime_report_at(
+        &self,
+        request: &RunRealtimeReportRequest,
+        now: DateTime<Utc>,
+    ) -> Result<RunRealtimeReportResponse, ClientError> {
+        let default_range = [MinuteRange {
+            name: None,
+            start_minutes_ago: Some(29),
+            end_minutes_ago: Some(0),
+        }];
+        let minute_ranges = match request.minute_ranges.as_deref() {
+            Some(ranges) if !ranges.is_empty() => ranges,
+            _ => &default_range,
+        };
+
+        let mut ranges = Vec::new();
+        let mut buckets = Vec::new();
+        for (index, range) in minute_ranges.iter().enumerate() {
+            let start = range.start_minutes_ago.unwrap_or(29);
+            let end = range.end_minutes_ago.unwrap_or(0);
+            if !(0..=59).contains(&start) || !(0..=59).contains(&end) || end > start {
+                return Err(ClientError::RequestFailed(format!(
+                    "Invalid minute range {}..{}",
+                    start, end
+                )));
+            }
+            ranges.push(range.name.clone().unwrap_or_else(|| format!("date_range_{}", index)));
+            for minutes_ago in end..=start {
+                let at = now - Duration::minutes(i64::from(minutes_ago));
+                buckets.push(Bucket {
+                    range: index,
+                    date: Some(at.date_naive()),
+                    hour: Some(at.hour()),
+                    minutes_ago: Some(minutes_ago as u32),
+                    sessions: self.minute_sessions(at),
+                });
+            }
+        }
+
+        let output = self.generate(Query {
+            dimensions: names(request.dimensions.as_deref().unwrap_or_default()),
+            metrics: request.metrics.iter().map(|m| m.name.clone()).collect(),
+            ranges,
+            buckets,
+            dimension_filter: request.dimension_filter.as_ref(),
+            metric_filter: request.metric_filter.as_ref(),
+            order_bys: request.order_bys.as_deref().unwrap_or_default(),
+            aggregations: request.metric_aggregations.as_deref().unwrap_or_default(),
+            keep_empty_rows: false,
+            offset: 0,
+            limit: limit(request.limit),
+        });
+
+        Ok(RunRealtimeReportResponse {
+            dimension_headers: Some(output.dimension_headers),
+            metric_headers: Some(output.metric_headers),
+            rows: Some(output.rows),
+            totals: output.totals,
+            maximums: output.maximums,
+            minimums: output.minimums,
+            row_count: Some(output.row_count as i32),
+            property_quota: request.return_property_quota.unwrap_or(false).then(quota),
+            kind: Some(DEMO_REPORT_KIND.to_string()),
+        })
+    }
+
+    /// Answer a pivot report request; the pivot fields become dimensions
+    /// of the rows
+    pub fn run_pivot_report(
+        &self,
+        request: &RunPivotReportRequest,
+    ) -> Result<RunPivotReportResponse, ClientError> {
+        let mut dimensions = request.dimensions.clone().unwrap_or_default();
+        for pivot in &request.pivots {
+            for field in &pivot.field_names {
+                if !dimensions.iter().any(|d| &d.name == field) {
+                    dimensions.push(Dimension { name: field.clone(), dimension_expression: None });
+                }
+            }
+        }
+
+        let report = self.run_report(&RunReportRequest {
+            property: request.property.clone(),
+            date_ranges: request.date_ranges.clone(),
+            dimensions: Some(dimensions),
+            metrics: request.metrics.clone(),
+            dimension_filter: request.dimension_filter.clone(),
+            metric_filter: request.metric_filter.clone(),
+            order_bys: None,
+            offset: None,
+            limit: None,
+            metric_aggregations: Some(vec!["TOTAL".to_string()]),
+            keep_empty_rows: request.keep_empty_rows,
+            return_property_quota: request.return_property_quota,
+        })?;
+
+        let headers = report.dimension_headers.clone().unwrap_or_default();
+        let rows = report.rows.clone().unwrap_or_default();
+        let pivot_headers = request
+            .pivots
+            .iter()
+            .map(|pivot| {
+                let columns: Vec<usize> = pivot
+                    .field_names
+                    .iter()
+                    .filter_map(|field| headers.iter().position(|h| &h.name == field))
+                    .collect();
+                let mut seen: Vec<Vec<String>> = Vec::new();
+                for row in &rows {
+                    let values = row.dimension_values.as_deref().unwrap_or_default();
+                    let key: Vec<String> = columns
+                        .iter()
+                        .map(|c| values.get(*c).and_then(|v| v.value.clone()).unwrap_or_default())
+                        .collect();
+                    if !seen.contains(&key) {
+                        seen.push(key);
+                    }
+                }
+                let count = seen.len();
+                let offset = pivot.offset.unwrap_or(0).max(0) as usize;
+                let take = pivot.limit.map_or(count, |l| l.max(0) as usize);
+                PivotHeader {
+                    pivot_dimension_headers: Some(
+                        seen.into_iter()
+                            .skip(offset)
+                            .take(take)
+                            .map(|values| PivotDimensionHeader {
+                                dimension_values: Some(values.into_iter().map(dimension_value).collect()),
+                            })
+                            .collect(),
+                    ),
+                    row_count: Some(count as i32),
+                }
+            })
+            .collect();
+
+        Ok(RunPivotReportResponse {
+            pivot_headers: Some(pivot_headers),
+            dimension_headers: report.dimension_headers,
+            metric_headers: report.metric_headers,
+            rows: report.rows,
+            aggregates: report.totals,
+            metadata: report.metadata,
+            property_quota: report.property_quota,
+            kind: Some(DEMO_REPORT_KIND.to_string()),
+        })
+    }
+
+    /// Answer a funnel report request: each step keeps part of the users
+    /// who reached the one before
+    pub fn run_funnel_report(
+        &self,
+        request: &RunFunnelReportRequest,
+    ) -> Result<serde_json::Value, ClientError> {
+        let report = self.run_report(&RunReportRequest {
+            property: request.property.clone(),
+            date_ranges: request.date_ranges.clone(),
+            dimensions: None,
+            metrics: vec![Metric { name: "activeUsers".to_string(), expression: None, invisible: None }],
+            dimension_filter: request.dimension_filter.clone(),
+            metric_filter: None,
+            order_bys: None,
+            offset: None,
+            limit: None,
+            metric_aggregations: None,
+            keep_empty_rows: Some(true),
+            return_property_quota: None,
+        })?;
+        let mut users: f64 = report
+            .rows
+            .iter()
+            .flatten()
+            .filter_map(|row| row.metric_values.as_ref()?.first()?.value.as_ref()?.parse::<f64>().ok())
+            .sum();
+
+        let mut rows = Vec::new();
+        for (index, step) in request.funnel.steps.iter().enumerate() {
+            let name = step.name.clone().unwrap_or_else(|| format!("Step {}", index + 1));
+            let kept = if index + 1 < request.funnel.steps.len() {
+                0.35 + 0.4 * self.unit(&["funnel", &index.to_string(), &name])
+            } else {
+                1.0
+            };
+            let next = (users * kept).round();
+            rows.push(serde_json::json!({
+                "dimensionValues": [{ "value": format!("{}. {}", index + 1, name) }],
+                "metricValues": [
+                    { "value": format_count(users) },
+                    { "value": format_ratio(if index + 1 < request.funnel.steps.len() { kept } else { 0.0 }) },
+                    { "value": format_count(users - next.min(users)) },
+                ],
+            }));
+            users = next;
+        }
+
+        Ok(serde_json::json!({
+            "funnelTable": {
+                "dimensionHeaders": [{ "name": "funnelStepName" }],
+                "metricHeaders": [
+                    { "name": "activeUsers", "type": "TYPE_INTEGER" },
+                    { "name": "funnelStepCompletionRate", "type": "TYPE_FLOAT" },
+                    { "name": "funnelStepAbandonments", "type": "TYPE_INTEGER" },
+                ],
+                "rows": rows,
+            },
+            "kind": DEMO_REPORT_KIND,
+        }))
+    }
+
+    /// Dimensions and metrics demo reports answer with
+    pub fn metadata(&self) -> Metadata {
+        let mut dimensions: Vec<&str> = TIME_DIMENSIONS.to_vec();
+        dimensions.extend(TABLES.iter().flat_map(|t| t.columns.iter().copied()));
+        dimensions.extend(ALIASES.iter().map(|(alias, _)| *alias));
+
+        Metadata {
+            name: Some(format!("properties/{}/metadata", DEMO_PROPERTY_ID)),
+            dimensions: Some(
+                dimensions
+                    .into_iter()
+                    .map(|name| DimensionMetadata {
+                        api_name: Some(name.to_string()),
+                        ui_name: Some(ui_name(name)),
+                        description: None,
+                        deprecated_api_names: None,
+                        custom_definition: Some(false),
+                        category: None,
+                    })
+                    .collect(),
+            ),
+            metrics: Some(
+                METRICS
+                    .iter()
+                    .map(|(name, metric_type)| MetricMetadata {
+                        api_name: Some(name.to_string()),
+                        ui_name: Some(ui_name(name)),
+                        description: None,
+                        metric_type: Some(*metric_type),
+                        expression: None,
+                        deprecated_api_names: None,
+                        custom_definition: Some(false),
+                        blocked_reasons: None,
+                        category: None,
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    /// The demo account and its property
+    pub fn account_summaries(&self) -> ListAccountSummariesResponse {
+        ListAccountSummariesResponse {
+            account_summaries: Some(vec![AccountSummary {
+                name: Some("accountSummaries/demo".to_string()),
+                account: Some("accounts/demo".to_string()),
+                display_name: Some("Demo Account".to_string()),
+                property_summaries: Some(vec![PropertySummary {
+                    property: Some(format!("properties/{}", DEMO_PROPERTY_ID)),
+                    display_name: Some("Demo Property".to_string()),
+                    property_type: Some("PROPERTY_TYPE_ORDINARY".to_string()),
+                }]),
+            }]),
+            next_page_token: None,
+        }
+    }
+
+    /// Dimensions, metrics and buckets of a report over `date_ranges`
+    fn report_query(
+        &self,
+        date_ranges: &[ApiDateRange],
+        dimensions: &[Dimension],
+        metrics: &[Metric],
+        now: DateTime<Utc>,
+    ) -> Result<Query<'static>, ClientError> {
+        if date_ranges.is_empty() {
+            return Err(ClientError::RequestFailed("At least one date range is required".to_string()));
+        }
+        let dimensions = names(dimensions);
+        let hourly = dimensions.iter().any(|d| d == "hour" || d == "dateHour");
+        let today = now.date_naive();
+
+        let mut ranges = Vec::new();
+        let mut buckets = Vec::new();
+        for (index, range) in date_ranges.iter().enumerate() {
+            let start = parse_date(&range.start_date, today)?;
+            let end = parse_date(&range.end_date, today)?;
+            if end < start {
+                return Err(ClientError::RequestFailed(format!(
+                    "Date range ends before it starts: {} to {}",
+                    range.start_date, range.end_date
+                )));
+            }
+            if (end - start).num_days() >= MAX_DAYS {
+                return Err(ClientError::RequestFailed("Date range is too long".to_string()));
+            }
+            ranges.push(range.name.clone().unwrap_or_else(|| format!("date_range_{}", index)));
+
+            let mut date = start;
+            while date <= end {
+                let sessions = if date > today { 0.0 } else { self.daily_sessions(date) };
+                if hourly {
+                    let day_total: f64 = HOURLY.iter().sum();
+                    for (hour, share) in HOURLY.iter().enumerate() {
+                        // Hours still to come today have no traffic
+                        let elapsed = date < today || (hour as u32) <= now.hour();
+                        buckets.push(Bucket {
+                            range: index,
+                            date: Some(date),
+                            hour: Some(hour as u32),
+                            minutes_ago: None,
+                            sessions: if elapsed { sessions * share / day_total } else { 0.0 },
+                        });
+                    }
+                } else {
+                    buckets.push(Bucket { range: index, date: Some(date), hour: None, minutes_ago: None, sessions });
+                }
+                date += Duration::days(1);
+            }
+        }
+
+        Ok(Query {
+            dimensions,
+            metrics: metrics.iter().map(|m| m.name.clone()).collect(),
+            ranges,
+            buckets,
+            dimension_filter: None,
+            metric_filter: None,
+            order_bys: &[],
+            aggregations: &[],
+            keep_empty_rows: false,
+            offset: 0,
+            limit: DEFAULT_LIMIT,
+        })
+    }
+
+    /// Build the rows, aggregates and headers of a query
+    fn generate(&self, query: Query<'_>) -> Output {
+        // Rows of reports over several ranges say which range they belong to
+        let mut dimensions = query.dimensions.clone();
+        if query.ranges.len() > 1 && !dimensions.iter().any(|d| d == DATE_RANGE_DIMENSION) {
+            dimensions.push(DATE_RANGE_DIMENSION.to_string());
+        }
+
+        // Filters may name dimensions the rows don't show
+        let mut split_by = dimensions.clone();
+        if let Some(filter) = query.dimension_filter {
+            for field in filter_fields(filter) {
+                if !split_by.contains(&field) {
+                    split_by.push(field);
+                }
+            }
+        }
+        let time_dimensions: Vec<&String> = dimensions.iter().filter(|d| source(d) == Source::Time).collect();
+        let (combinations, data_loss) = self.combinations(&split_by);
+
+        let mut aggregated: BTreeMap<(usize, Vec<String>, usize), Totals> = BTreeMap::new();
+        for bucket in &query.buckets {
+            let time_key: Vec<String> = time_dimensions.iter().map(|d| bucket.time_value(d)).collect();
+            for (index, combination) in combinations.iter().enumerate() {
+                if let Some(filter) = query.dimension_filter {
+                    let lookup = |field: &str| match source(field) {
+                        Source::Time => Some(bucket.time_value(field)),
+                        Source::Range => query.ranges.get(bucket.range).cloned(),
+                        _ => combination.values.get(field).cloned(),
+                    };
+                    if !matches_filter(filter, &lookup) {
+                        continue;
+                    }
+                }
+                let totals = combination.profile.totals(bucket.sessions * combination.weight);
+                aggregated
+                    .entry((bucket.range, time_key.clone(), index))
+                    .or_default()
+                    .add(&totals);
+            }
+        }
+
+        let metric_headers: Vec<MetricHeader> = query
+            .metrics
+            .iter()
+            .map(|name| MetricHeader { name: name.clone(), metric_type: Some(metric_type(name)) })
+            .collect();
+
+        // Rows with their dimension values, totals and range
+        let mut rows: Vec<(Vec<String>, Vec<f64>, Totals, usize)> = Vec::new();
+        for ((range, time_key, index), totals) in aggregated {
+            if totals.is_empty() && !query.keep_empty_rows {
+                continue;
+            }
+            let combination = &combinations[index];
+            let values: Vec<String> = dimensions
+                .iter()
+                .map(|d| match source(d) {
+                    Source::Time => {
+                        let position = time_dimensions.iter().position(|t| *t == d).unwrap_or(0);
+                        time_key.get(position).cloned().unwrap_or_default()
+                    }
+                    Source::Range => query.ranges.get(range).cloned().unwrap_or_default(),
+                    _ => combination.values.get(d.as_str()).cloned().unwrap_or_default(),
+                })
+                .collect();
+            let metrics: Vec<f64> = query.metrics.iter().map(|m| self.metric_value(&totals, m)).collect();
+            if let Some(filter) = query.metric_filter {
+                let lookup = |field: &str| {
+                    query
+                        .metrics
+                        .iter()
+                        .position(|m| m == field)
+                        .map(|i| format_metric(metrics[i], metric_type(field)))
+                };
+                if !matches_filter(filter, &lookup) {
+                    continue;
+                }
+            }
+            rows.push((values, metrics, totals, range));
+        }
+
+        // Aggregates over every row, one per date range
+        let aggregate = |label: &str, pick: &Pick<'_>| -> Vec<Row> {
+            (0..query.ranges.len())
+                .filter(|range| query.ranges.len() > 1 || *range == 0)
+                .map(|range| {
+                    let in_range: Vec<&(Vec<String>, Vec<f64>, Totals, usize)> =
+                        rows.iter().filter(|row| row.3 == range).collect();
+                    let mut totals = Totals::default();
+                    for row in &in_range {
+                        totals.add(&row.2);
+                    }
+                    let values: Vec<&Vec<f64>> = in_range.iter().map(|row| &row.1).collect();
+                    Row {
+                        dimension_values: Some(
+                            dimensions
+                                .iter()
+                                .map(|d| {
+                                    if d == DATE_RANGE_DIMENSION {
+                                        dimension_value(query.ranges[range].clone())
+                                    } else {
+                                        dimension_value(label.to_string())
+                                    }
+                                })
+                                .collect(),
+                        ),
+                        metric_values: Some(
+                            query
+                                .metrics
+                                .iter()
+                                .enumerate()
+                                .map(|(i, name)| metric_value(pick(&values, i, &totals), name))
+                                .collect(),
+                        ),
+                    }
+                })
+                .collect()
+        };
+        let wants = |name: &str| query.aggregations.iter().any(|a| a == name);
+        let totals = wants("TOTAL").then(|| {
+            aggregate("RESERVED_TOTAL", &|_, i, totals| self.metric_value(totals, &query.metrics[i]))
+        });
+        let maximums = wants("MAXIMUM").then(|| {
+            aggregate("RESERVED_MAX", &|values, i, _| {
+                values.iter().map(|v| v[i]).fold(None, |max: Option<f64>, v| Some(max.map_or(v, |m| m.max(v)))).unwrap_or(0.0)
+            })
+        });
+        let minimums = wants("MINIMUM").then(|| {
+            aggregate("RESERVED_MIN", &|values, i, _| {
+                values.iter().map(|v| v[i]).fold(None, |min: Option<f64>, v| Some(min.map_or(v, |m| m.min(v)))).unwrap_or(0.0)
+            })
+        });
+
+        if !query.order_bys.is_empty() {
+            rows.sort_by(|a, b| {
+                for order in query.order_bys {
+                    let ordering = if let Some(metric) = &order.metric {
+                        let i = query.metrics.iter().position(|m| *m == metric.metric_name);
+                        i.map_or(std::cmp::Ordering::Equal, |i| a.1[i].total_cmp(&b.1[i]))
+                    } else if let Some(dimension) = &order.dimension {
+                        let i = dimensions.iter().position(|d| *d == dimension.dimension_name);
+                        i.map_or(std::cmp::Ordering::Equal, |i| {
+                            compare_dimension(&a.0[i], &b.0[i], dimension.order_type)
+                        })
+                    } else {
+                        std::cmp::Ordering::Equal
+                    };
+                    let ordering = if order.desc.unwrap_or(false) { ordering.reverse() } else { ordering };
+                    if ordering.is_ne() {
+                        return ordering;
+                    }
+                }
+                std::cmp::Ordering::Equal
+            });
+        }
+
+        let row_count = rows.len();
+        let rows = rows
+            .into_iter()
+            .skip(query.offset)
+            .take(query.limit)
+            .map(|(values, metrics, _, _)| Row {
+                dimension_values: Some(values.into_iter().map(dimension_value).collect()),
+                metric_values: Some(
+                    metrics
+                        .iter()
+                        .zip(&query.metrics)
+                        .map(|(value, name)| metric_value(*value, name))
+                        .collect(),
+                ),
+            })
+            .collect();
+
+        Output {
+            dimension_headers: dimensions.iter().map(|name| DimensionHeader { name: name.clone() }).collect(),
+            metric_headers,
+            rows,
+            totals,
+            maximums,
+            minimums,
+            row_count,
+            data_loss,
+        }
+    }
+
+    /// Value combinations of the traffic dimensions among `dimensions`,
+    /// heaviest first, with their share of the traffic. True when some
+    /// were dropped
+    fn combinations(&self, dimensions: &[String]) -> (Vec<Combination>, bool) {
+        // Values of each table, projected onto the requested columns
+        let mut groups: Vec<Vec<(Values, f64)>> = Vec::new();
+        let mut tables: BTreeMap<usize, Vec<(&String, usize)>> = BTreeMap::new();
+        for dimension in dimensions {
+            match source(dimension) {
+                Source::Table(table, column) => tables.entry(table).or_default().push((dimension, column)),
+                Source::Synthetic => groups.push(self.synthetic_values(dimension)),
+                Source::Time | Source::Range => {}
+            }
+        }
+        for (table, columns) in tables {
+            let mut projected: Vec<(Values, f64)> = Vec::new();
+            for (index, (row, weight)) in TABLES[table].rows.iter().enumerate() {
+                let weight = weight * (0.7 + 0.6 * self.unit(&["table", &table.to_string(), &index.to_string()]));
+                let values: Values = columns
+                    .iter()
+                    .map(|(dimension, column)| ((*dimension).clone(), row[*column].to_string()))
+                    .collect();
+                match projected.iter_mut().find(|(v, _)| *v == values) {
+                    Some((_, total)) => *total += weight,
+                    None => projected.push((values, weight)),
+                }
+            }
+            groups.push(projected);
+        }
+
+        let mut combinations: Vec<(Values, f64)> = vec![(Vec::new(), 1.0)];
+        let mut data_loss = false;
+        for group in groups {
+            let total: f64 = group.iter().map(|(_, w)| w).sum();
+            let mut next = Vec::with_capacity(combinations.len() * group.len());
+            for (values, weight) in &combinations {
+                for (more, share) in &group {
+                    let mut values = values.clone();
+                    values.extend(more.iter().cloned());
+                    next.push((values, weight * share / total));
+                }
+            }
+            next.sort_by(|a, b| b.1.total_cmp(&a.1));
+            if next.len() > MAX_COMBINATIONS {
+                next.truncate(MAX_COMBINATIONS);
+                data_loss = true;
+            }
+            combinations = next;
+        }
+
+        let combinations = combinations
+            .into_iter()
+            .map(|(values, weight)| {
+                let key: Vec<&str> = values.iter().map(|(_, v)| v.as_str()).collect();
+                Combination { profile: self.profile(&key), values: values.into_iter().collect(), weight }
+            })
+            .collect();
+        (combinations, data_loss)
+    }
+
+    /// Values of a dimension the generator doesn't know
+    fn synthetic_values(&self, dimension: &str) -> Vec<(Values, f64)> {
+        let mut values = vec![(vec![(dimension.to_string(), "(not set)".to_string())], 0.3)];
+        for n in 1..=5 {
+            values.push((
+                vec![(dimension.to_string(), format!("{} {}", ui_name(dimension), n))],
+                0.35 / n as f64 * (0.7 + 0.6 * self.unit(&["synthetic", dimension, &n.to_string()])),
+            ));
+        }
+        values
+    }
+
+    /// Behavior of the traffic with the dimension values `key`
+    fn profile(&self, key: &[&str]) -> Profile {
+        let vary = |name: &str, spread: f64| {
+            let mut parts = vec!["profile", name];
+            parts.extend_from_slice(key);
+            1.0 - spread + 2.0 * spread * self.unit(&parts)
+        };
+        Profile {
+            users_per_session: 0.78 * vary("users", 0.05),
+            new_user_share: (0.6 * vary("new", 0.2)).min(0.95),
+            pages_per_session: 2.4 * vary("pages", 0.25),
+            seconds_per_session: 150.0 * vary("duration", 0.4),
+            engagement: (0.58 * vary("engagement", 0.2)).min(0.95),
+            events_per_view: 3.2 * vary("events", 0.15),
+            conversion_rate: 0.025 * vary("conversions", 0.5),
+            purchase_rate: 0.009 * vary("purchases", 0.5),
+            order_value: 68.0 * vary("order", 0.3),
+        }
+    }
+
+    /// Sessions on a day, before splitting by dimension
+    fn daily_sessions(&self, date: NaiveDate) -> f64 {
+        let epoch = NaiveDate::from_ymd_opt(EPOCH.0, EPOCH.1, EPOCH.2).unwrap_or_default();
+        let days = (date - epoch).num_days() as f64;
+        let base = 600.0 + 900.0 * self.unit(&["base"]);
+        let trend = (1.0 + 0.2 * days / 365.0).max(0.3);
+        let season = 1.0 + 0.08 * (2.0 * PI * f64::from(date.ordinal()) / 365.0).sin();
+        let weekday = WEEKDAYS[date.weekday().num_days_from_monday() as usize];
+        let noise = 0.88 + 0.24 * self.unit(&["day", &date.to_string()]);
+        base * trend * season * weekday * noise
+    }
+
+    /// Sessions starting in the minute of `at`
+    fn minute_sessions(&self, at: DateTime<Utc>) -> f64 {
+        let day_total: f64 = HOURLY.iter().sum();
+        let hour_share = HOURLY[at.hour() as usize] / day_total;
+        let noise = 0.7 + 0.6 * self.unit(&["minute", &at.format("%Y%m%d%H%M").to_string()]);
+        self.daily_sessions(at.date_naive()) * hour_share / 60.0 * noise
+    }
+
+    /// Value of a metric over some traffic
+    fn metric_value(&self, t: &Totals, name: &str) -> f64 {
+        let ratio = |a: f64, b: f64| if b > 0.0 { a / b } else { 0.0 };
+        match name {
+            "sessions" => t.sessions,
+            "totalUsers" | "activeUsers" | "active1DayUsers" => t.users,
+            "newUsers" => t.new_users,
+            "screenPageViews" | "views" => t.views,
+            "screenPageViewsPerSession" => ratio(t.views, t.sessions),
+            "screenPageViewsPerUser" => ratio(t.views, t.users),
+            "averageSessionDuration" => ratio(t.duration, t.sessions),
+            "userEngagementDuration" => t.duration,
+            "engagedSessions" => t.engaged,
+            "engagementRate" => ratio(t.engaged, t.sessions),
+            "bounceRate" => ratio(t.sessions - t.engaged, t.sessions),
+            "sessionsPerUser" => ratio(t.sessions, t.users),
+            "eventCount" => t.events,
+            "eventsPerSession" => ratio(t.events, t.sessions),
+            "conversions" | "keyEvents" => t.conversions,
+            "sessionConversionRate" | "sessionKeyEventRate" => ratio(t.conversions, t.sessions),
+            "ecommercePurchases" | "transactions" => t.purchases,
+            "totalRevenue" | "purchaseRevenue" => t.revenue,
+            "averagePurchaseRevenue" => ratio(t.revenue, t.purchases),
+            // Counts the generator doesn't know follow the sessions
+            other => t.sessions * (0.1 + 0.9 * self.unit(&["metric", other])),
+        }
+    }
+
+    /// Deterministic number in [0, 1) for the seed and `parts`
+    fn unit(&self, parts: &[&str]) -> f64 {
+        let mut hash = 0xcbf2_9ce4_8422_2325_u64 ^ self.seed;
+        for part in parts {
+            for byte in part.bytes().chain(std::iter::once(0xff)) {
+                hash ^= u64::from(byte);
+                hash = hash.wrapping_mul(0x0100_0000_01b3);
+            }
+        }
+        (mix(hash) >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+impl Default for DemoData {
+    fn default() -> Self {
+        Self::new(DEFAULT_DEMO_SEED)
+    }
+}
+
+/// Final mix of splitmix64, spreading every input bit over the output
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+fn names(dimensions: &[Dimension]) -> Vec<String> {
+    dimensions.iter().map(|d| d.name.clone()).collect()
+}
+
+fn limit(limit: Option<i64>) -> usize {
+    match limit {
+        Some(limit) if limit > 0 => limit as usize,
+        _ => DEFAULT_LIMIT,
+    }
+}
+
+/// Date of a GA date string: `YYYY-MM-DD`, `today`, `yesterday` or `NdaysAgo`
+fn parse_date(value: &str, today: NaiveDate) -> Result<NaiveDate, ClientError> {
+    let parsed = match value {
+        "today" => Some(today),
+        "yesterday" => Some(today - Duration::days(1)),
+        _ => match value.strip_suffix("daysAgo") {
+            Some(days) => days.parse::<i64>().ok().map(|days| today - Duration::days(days)),
+            None => NaiveDate::parse_from_str(value, "%Y-%m-%d").ok(),
+        },
+    };
+    parsed.ok_or_else(|| ClientError::RequestFailed(format!("Invalid date: {}", value)))
+}
+
+fn metric_type(name: &str) -> MetricType {
+    METRICS
+        .iter()
+        .find(|(metric, _)| *metric == name)
+        .map_or(MetricType::TypeInteger, |(_, metric_type)| *metric_type)
+}
+
+fn format_count(value: f64) -> String {
+    format!("{}", value.round().max(0.0) as i64)
+}
+
+fn format_ratio(value: f64) -> String {
+    format!("{}", (value * 1e6).round() / 1e6)
+}
+
+fn format_metric(value: f64, metric_type: MetricType) -> String {
+    match metric_type {
+        MetricType::TypeInteger => format_count(value),
+        MetricType::TypeCurrency => format!("{}", (value * 100.0).round() / 100.0),
+        _ => format_ratio(value),
+    }
+}
+
+fn metric_value(value: f64, name: &str) -> MetricValue {
+    MetricValue { value: Some(format_metric(value, metric_type(name))), one_value: None }
+}
+
+fn dimension_value(value: String) -> DimensionValue {
+    DimensionValue { value: Some(value), one_value: None }
+}
+
+/// `sessionSource` as "Session source"
+fn ui_name(api_name: &str) -> String {
+    let mut name = String::new();
+    for (i, c) in api_name.chars().enumerate() {
+        if i == 0 {
+            name.extend(c.to_uppercase());
+        } else if c.is_uppercase() {
+            name.push(' ');
+            name.extend(c.to_lowercase());
+        } else {
+            name.push(c);
+        }
+    }
+    name
+}
+
+/// Quota of a property barely used
+fn quota() -> PropertyQuota {
+    let status = |consumed: i32, remaining: i32| Some(QuotaStatus { consumed: Some(consumed), remaining: Some(remaining) });
+    PropertyQuota {
+        tokens_per_day: status(12, 199_988),
+        tokens_per_hour: status(12, 39_988),
+        concurrent_requests: status(0, 10),
+        server_errors_per_project_per_hour: status(0, 10),
+        potentially_thresholded_requests_per_hour: status(0, 120),
+        tokens_per_project_per_hour: status(12, 13_988),
+    }
+}
+
+fn compare_dimension(a: &str, b: &str, order_type: Option<DimensionOrderType>) -> std::cmp::Ordering {
+    match order_type {
+        Some(DimensionOrderType::CaseInsensitiveAlphanumeric) => a.to_lowercase().cmp(&b.to_lowercase()),
+        Some(DimensionOrderType::Numeric) => {
+            let number = |v: &str| v.parse::<f64>().unwrap_or(f64::NEG_INFINITY);
+            number(a).total_cmp(&number(b))
+        }
+        _ => a.cmp(b),
+    }
+}
+
+/// Fields a filter expression tests
+fn filter_fields(expression: &FilterExpression) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut pending = vec![expression];
+    while let Some(expression) = pending.pop() {
+        for group in [&expression.and_group, &expression.or_group].into_iter().flatten() {
+            pending.extend(group.expressions.iter());
+        }
+        if let Some(not) = &expression.not_expression {
+            pending.push(not);
+        }
+        if let Some(filter) = &expression.filter {
+            fields.push(filter.field_name.clone());
+        }
+    }
+    fields
+}
+
+/// Whether the values `lookup` gives pass a filter expression
+fn matches_filter(expression: &FilterExpression, lookup: &dyn Fn(&str) -> Option<String>) -> bool {
+    if let Some(group) = &expression.and_group {
+        return group.expressions.iter().all(|e| matches_filter(e, lookup));
+    }
+    if let Some(group) = &expression.or_group {
+        return group.expressions.iter().any(|e| matches_filter(e, lookup));
+    }
+    if let Some(not) = &expression.not_expression {
+        return !matches_filter(not, lookup);
+    }
+    let Some(filter) = &expression.filter else {
+        return true;
+    };
+    let Some(value) = lookup(&filter.field_name) else {
+        return false;
+    };
+
+    if let Some(string) = &filter.string_filter {
+        let (value, expected) = if string.case_sensitive.unwrap_or(false) {
+            (value, string.value.clone())
+        } else {
+            (value.to_lowercase(), string.value.to_lowercase())
+        };
+        return match string.match_type {
+            StringFilterMatchType::Exact | StringFilterMatchType::FullRegexp => value == expected,
+            StringFilterMatchType::BeginsWith => value.starts_with(&expected),
+            StringFilterMatchType::EndsWith => value.ends_with(&expected),
+            StringFilterMatchType::Contains | StringFilterMatchType::PartialRegexp => {
+                value.contains(&expected)
+            }
+        };
+    }
+    if let Some(list) = &filter.in_list_filter {
+        return if list.case_sensitive.unwrap_or(false) {
+            list.values.contains(&value)
+        } else {
+            list.values.iter().any(|v| v.eq_ignore_ascii_case(&value))
+        };
+    }
+
+    let number = |v: &NumericValue| {
+        v.double_value.or_else(|| v.int64_value.as_deref().and_then(|i| i.parse().ok()))
+    };
+    let Ok(actual) = value.parse::<f64>() else {
+        return false;
+    };
+    if let Some(numeric) = &filter.numeric_filter {
+        let Some(expected) = number(&numeric.value) else {
+            return false;
+        };
+        return match numeric.operation {
+            NumericFilterOperation::Equal => actual == expected,
+            NumericFilterOperation::LessThan => actual < expected,
+            NumericFilterOperation::LessThanOrEqual => actual <= expected,
+            NumericFilterOperation::GreaterThan => actual > expected,
+            NumericFilterOperation::GreaterThanOrEqual => actual >= expected,
+        };
+    }
+    if let Some(between) = &filter.between_filter {
+        return match (number(&between.from_value), number(&between.to_value)) {
+            (Some(from), Some(to)) => from <= actual && actual <= to,
+            _ => false,
+        };
+    }
+    true
+}
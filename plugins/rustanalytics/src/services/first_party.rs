@@ -0,0 +1,250 @@
+//! First-party Collection Service
+//!
+//! Page hits recorded by RustPress itself, independent of Google Analytics.
+//! The realtime dashboard falls back to this data when GA4 is unavailable.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use parking_lot::RwLock;
+
+use crate::models::realtime::*;
+use crate::services::client::ClientError;
+
+/// How far back realtime data reaches, in minutes
+const REALTIME_WINDOW_MINUTES: i64 = 30;
+
+/// Rows returned per top list
+const TOP_LIMIT: usize = 10;
+
+/// Source of realtime data that does not depend on Google Analytics
+#[async_trait]
+pub trait FirstPartySource: Send + Sync {
+    /// Build a realtime overview from first-party data
+    async fn realtime_overview(&self) -> Result<RealtimeOverview, ClientError>;
+}
+
+/// In-process collector of the last 30 minutes of page hits
+#[derive(Debug, Default)]
+pub struct FirstPartyCollector {
+    hits: RwLock<VecDeque<RealtimePageHit>>,
+}
+
+impl FirstPartyCollector {
+    /// Create an empty collector
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a page hit
+    pub fn record(&self, hit: RealtimePageHit) {
+        let mut hits = self.hits.write();
+        hits.push_back(hit);
+        Self::prune(&mut hits, Utc::now());
+    }
+
+    /// Number of hits currently held
+    pub fn len(&self) -> usize {
+        self.hits.read().len()
+    }
+
+    /// Whether no hits are held
+    pub fn is_empty(&self) -> bool {
+        self.hits.read().is_empty()
+    }
+
+    /// Build a realtime overview as of `now`
+    pub fn overview_at(&self, now: DateTime<Utc>) -> RealtimeOverview {
+        let window_start = now - Duration::minutes(REALTIME_WINDOW_MINUTES);
+        let hits = self.hits.read();
+        let recent: Vec<&RealtimePageHit> = hits
+            .iter()
+            .filter(|h| h.timestamp > window_start && h.timestamp <= now)
+            .collect();
+
+        let active_within = |minutes: i64| {
+            let since = now - Duration::minutes(minutes);
+            recent
+                .iter()
+                .filter(|h| h.timestamp > since)
+                .map(|h| h.session_id.as_str())
+                .collect::<HashSet<_>>()
+                .len() as u32
+        };
+        let active_users_30min = active_within(REALTIME_WINDOW_MINUTES);
+
+        let pageviews_per_minute = (0..REALTIME_WINDOW_MINUTES)
+            .map(|minute| {
+                let end = now - Duration::minutes(minute);
+                let start = end - Duration::minutes(1);
+                PageviewsPerMinute {
+                    minute: minute as u32,
+                    pageviews: recent
+                        .iter()
+                        .filter(|h| h.timestamp > start && h.timestamp <= end)
+                        .count() as u32,
+                    timestamp: end,
+                }
+            })
+            .collect();
+
+        let percentage = |users: u32| {
+            if active_users_30min > 0 {
+                (users as f64 / active_users_30min as f64) * 100.0
+            } else {
+                0.0
+            }
+        };
+
+        let top_active_pages = top_by(&recent, |h| h.page_path.clone())
+            .into_iter()
+            .map(|(page_path, active_users)| ActivePage {
+                page_title: recent
+                    .iter()
+                    .rev()
+                    .find(|h| h.page_path == page_path)
+                    .map(|h| h.page_title.clone())
+                    .unwrap_or_default(),
+                page_path,
+                active_users,
+                percentage: percentage(active_users),
+            })
+            .collect();
+
+        let top_referrers = top_by(&recent, |h| h.referrer.as_deref().map(referrer_host).unwrap_or_default())
+            .into_iter()
+            .filter(|(referrer, _)| !referrer.is_empty())
+            .map(|(referrer, active_users)| ActiveReferrer {
+                referrer,
+                active_users,
+                percentage: percentage(active_users),
+            })
+            .collect();
+
+        let top_traffic_sources = top_by(&recent, |h| match h.referrer.as_deref().map(referrer_host) {
+            Some(host) if !host.is_empty() => host,
+            _ => "(direct)".to_string(),
+        })
+        .into_iter()
+        .map(|(source, active_users)| ActiveTrafficSource {
+            medium: if source == "(direct)" { "(none)" } else { "referral" }.to_string(),
+            source,
+            active_users,
+            percentage: percentage(active_users),
+        })
+        .collect();
+
+        let top_locations = top_by(&recent, |h| h.country.clone())
+            .into_iter()
+            .map(|(country, active_users)| ActiveLocation {
+                country,
+                country_code: String::new(),
+                region: None,
+                city: None,
+                latitude: None,
+                longitude: None,
+                active_users,
+                percentage: percentage(active_users),
+            })
+            .collect();
+
+        let devices = count_sessions_by(&recent, |h| h.device_category.to_lowercase());
+        let device = |category: &str| {
+            let active_users = devices.get(category).copied().unwrap_or(0);
+            DeviceStats {
+                active_users,
+                percentage: percentage(active_users),
+            }
+        };
+
+        RealtimeOverview {
+            active_users: active_within(1),
+            active_users_1min: active_within(1),
+            active_users_5min: active_within(5),
+            active_users_10min: active_within(10),
+            active_users_30min,
+            pageviews_per_minute,
+            pageviews_per_second: Vec::new(),
+            top_active_pages,
+            top_referrers,
+            top_keywords: Vec::new(),
+            top_locations,
+            top_traffic_sources,
+            top_social_sources: Vec::new(),
+            device_breakdown: DeviceBreakdown {
+                desktop: device("desktop"),
+                mobile: device("mobile"),
+                tablet: device("tablet"),
+            },
+            active_events: Vec::new(),
+            active_conversions: Vec::new(),
+            timestamp: now,
+        }
+    }
+
+    /// Drop hits that have left the realtime window
+    fn prune(hits: &mut VecDeque<RealtimePageHit>, now: DateTime<Utc>) {
+        let window_start = now - Duration::minutes(REALTIME_WINDOW_MINUTES);
+        while hits.front().is_some_and(|h| h.timestamp <= window_start) {
+            hits.pop_front();
+        }
+    }
+}
+
+#[async_trait]
+impl FirstPartySource for FirstPartyCollector {
+    async fn realtime_overview(&self) -> Result<RealtimeOverview, ClientError> {
+        Self::prune(&mut self.hits.write(), Utc::now());
+        Ok(self.overview_at(Utc::now()))
+    }
+}
+
+/// Distinct sessions per key
+fn count_sessions_by<F>(hits: &[&RealtimePageHit], key: F) -> HashMap<String, u32>
+where
+    F: Fn(&RealtimePageHit) -> String,
+{
+    let mut sessions: HashMap<String, HashSet<&str>> = HashMap::new();
+    for hit in hits {
+        sessions.entry(key(hit)).or_default().insert(hit.session_id.as_str());
+    }
+    sessions
+        .into_iter()
+        .map(|(key, sessions)| (key, sessions.len() as u32))
+        .collect()
+}
+
+/// Keys with the most distinct sessions, busiest first
+fn top_by<F>(hits: &[&RealtimePageHit], key: F) -> Vec<(String, u32)>
+where
+    F: Fn(&RealtimePageHit) -> String,
+{
+    let mut counts: Vec<(String, u32)> = count_sessions_by(hits, key).into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts.truncate(TOP_LIMIT);
+    counts
+}
+
+/// Host part of a referrer URL
+fn referrer_host(referrer: &str) -> String {
+    let without_scheme = referrer.split("://").nth(1).unwrap_or(referrer);
+    without_scheme
+        .split(['/', '?', '#'])
+        .next()
+        .unwrap_or_default()
+        .trim_start_matches("www.")
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_referrer_host() {
+        assert_eq!(referrer_host("https://www.Google.com/search?q=rust"), "google.com");
+        assert_eq!(referrer_host("news.ycombinator.com/item"), "news.ycombinator.com");
+        assert_eq!(referrer_host(""), "");
+    }
+}
@@ -4,15 +4,31 @@
 //! Google Analytics API and managing analytics data.
 
 pub mod client;
+pub mod coordinator;
+pub mod demo;
 pub mod analytics;
 pub mod realtime;
+pub mod first_party;
+pub mod podcast;
+pub mod web_vitals;
 pub mod reports;
 pub mod cache;
 pub mod sync;
+pub mod snapshot;
+pub mod privacy;
+pub mod delivery;
 
 pub use client::GoogleAnalyticsClient;
+pub use coordinator::{RequestCoordinator, RequestLimits, RequestPriority};
+pub use demo::DemoData;
 pub use analytics::AnalyticsService;
 pub use realtime::RealtimeService;
+pub use first_party::{FirstPartyCollector, FirstPartySource};
+pub use podcast::PodcastDownloadSource;
+pub use web_vitals::WebVitalsSource;
 pub use reports::ReportService;
 pub use cache::CacheService;
 pub use sync::SyncService;
+pub use snapshot::OverviewSnapshotService;
+pub use privacy::PrivacyPolicy;
+pub use delivery::{MemorySecretStore, ReportDelivery, SecretStore};
@@ -0,0 +1,22 @@
+//! Podcast Downloads Service
+//!
+//! Episode downloads are counted by RustPress itself: the host serves
+//! episode audio and logs each listener, and hands the plugin a
+//! [`PodcastDownloadSource`] so behavior reports can show downloads next
+//! to pageviews.
+
+use async_trait::async_trait;
+
+use crate::models::behavior::PodcastDownloadsOverview;
+use crate::models::DateRange;
+use crate::services::client::ClientError;
+
+/// Source of podcast download statistics, provided by the host
+#[async_trait]
+pub trait PodcastDownloadSource: Send + Sync {
+    /// Downloads per day, episode and podcast app over a date range
+    async fn downloads_overview(
+        &self,
+        date_range: &DateRange,
+    ) -> Result<PodcastDownloadsOverview, ClientError>;
+}
@@ -0,0 +1,386 @@
+//! Privacy Policy
+//!
+//! Column-level privacy controls applied by the client to every GA request:
+//!
+//! - redacted dimensions are never requested; GA aggregates over them and
+//!   their column reads "(redacted)" so report shapes don't change
+//! - requests filtering on a redacted dimension are refused, since the
+//!   filter would reveal what the column hides
+//! - rows of breakdowns with fewer users than the minimum are hidden;
+//!   reports split only by time are left alone as they show nothing the
+//!   totals don't
+//!
+//! Every redaction is recorded in the response metadata, and from there in
+//! the data quality of the reports built from it, for compliance audits.
+
+use std::collections::BTreeSet;
+
+use crate::models::api::*;
+use crate::models::{AnalyticsSettings, PrivacyRedaction};
+use crate::services::client::ClientError;
+
+/// Value shown in place of a redacted dimension
+pub const REDACTED_VALUE: &str = "(redacted)";
+
+/// Location dimensions finer than a region
+pub const CITY_GEO_DIMENSIONS: &[&str] = &["city", "cityId"];
+
+/// Dimensions describing who users are
+pub const DEMOGRAPHIC_DIMENSIONS: &[&str] = &["userAgeBracket", "userGender", "brandingInterest"];
+
+/// Dimensions that split time; breakdowns by them alone aren't thresholded
+const TIME_DIMENSIONS: &[&str] = &[
+    "date", "dateHour", "dateHourMinute", "day", "dayOfWeek", "dayOfWeekName", "hour",
+    "isoWeek", "isoYear", "isoYearIsoWeek", "minute", "month", "nthDay", "nthHour",
+    "nthMinute", "nthMonth", "nthWeek", "nthYear", "week", "year", "yearMonth", "yearWeek",
+    "minutesAgo", "dateRange",
+];
+
+/// Users metric read for the minimum-users threshold of reports
+const REPORT_USERS_METRIC: &str = "totalUsers";
+
+/// Users metric read for the minimum-users threshold of realtime reports
+const REALTIME_USERS_METRIC: &str = "activeUsers";
+
+/// Privacy controls of the plugin settings
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PrivacyPolicy {
+    redacted_dimensions: BTreeSet<String>,
+    min_users: u32,
+}
+
+/// What a policy changed in a request, undone in its response
+#[derive(Debug, Clone, Default)]
+pub struct PreparedRequest {
+    /// Redacted dimensions removed from the request, with their position
+    removed: Vec<(usize, String)>,
+    /// Position of the users metric when rows are thresholded
+    users_metric: Option<usize>,
+    /// The users metric was added for the threshold and isn't shown
+    users_metric_added: bool,
+}
+
+/// Parts of a report response the policy rewrites
+struct ResponseParts<'a> {
+    dimension_headers: &'a mut Option<Vec<DimensionHeader>>,
+    metric_headers: &'a mut Option<Vec<MetricHeader>>,
+    rows: &'a mut Option<Vec<Row>>,
+    aggregates: [&'a mut Option<Vec<Row>>; 3],
+    row_count: &'a mut Option<i32>,
+}
+
+impl PrivacyPolicy {
+    /// Build the policy from the plugin settings
+    pub fn from_settings(settings: &AnalyticsSettings) -> Self {
+        let mut redacted_dimensions: BTreeSet<String> = settings
+            .redacted_dimensions
+            .iter()
+            .map(|d| d.trim())
+            .filter(|d| !d.is_empty())
+            .map(str::to_string)
+            .collect();
+        if settings.redact_city_geo {
+            redacted_dimensions.extend(CITY_GEO_DIMENSIONS.iter().map(|d| d.to_string()));
+        }
+        if settings.redact_demographics {
+            redacted_dimensions.extend(DEMOGRAPHIC_DIMENSIONS.iter().map(|d| d.to_string()));
+        }
+
+        Self {
+            redacted_dimensions,
+            min_users: settings.min_users_per_row,
+        }
+    }
+
+    /// Whether the policy changes nothing
+    pub fn is_empty(&self) -> bool {
+        self.redacted_dimensions.is_empty() && self.min_users == 0
+    }
+
+    /// Whether `dimension` is withheld
+    pub fn is_redacted(&self, dimension: &str) -> bool {
+        self.redacted_dimensions.contains(dimension)
+    }
+
+    /// Dimensions withheld from every report
+    pub fn redacted_dimensions(&self) -> impl Iterator<Item = &str> {
+        self.redacted_dimensions.iter().map(String::as_str)
+    }
+
+    /// Fewest users a row of a breakdown must have to be shown; 0 shows
+    /// every row
+    pub fn min_users(&self) -> u32 {
+        self.min_users
+    }
+
+    /// Rewrite a report request so GA never returns withheld data
+    pub fn prepare(&self, request: &mut RunReportRequest) -> Result<PreparedRequest, ClientError> {
+        self.check_filter(request.dimension_filter.as_ref())?;
+        Ok(self.prepare_parts(
+            &mut request.dimensions,
+            &mut request.metrics,
+            &mut request.order_bys,
+            REPORT_USERS_METRIC,
+        ))
+    }
+
+    /// Rewrite a realtime report request so GA never returns withheld data
+    pub fn prepare_realtime(
+        &self,
+        request: &mut RunRealtimeReportRequest,
+    ) -> Result<PreparedRequest, ClientError> {
+        self.check_filter(request.dimension_filter.as_ref())?;
+        Ok(self.prepare_parts(
+            &mut request.dimensions,
+            &mut request.metrics,
+            &mut request.order_bys,
+            REALTIME_USERS_METRIC,
+        ))
+    }
+
+    /// Refuse a pivot report naming a withheld dimension; pivots can't
+    /// show a placeholder column
+    pub fn check_pivot(&self, request: &RunPivotReportRequest) -> Result<(), ClientError> {
+        self.check_filter(request.dimension_filter.as_ref())?;
+        let pivot_fields = request.pivots.iter().flat_map(|p| p.field_names.iter());
+        let dimensions = request.dimensions.iter().flatten().flat_map(referenced_dimensions);
+        match dimensions.chain(pivot_fields.map(String::as_str)).find(|d| self.is_redacted(d)) {
+            Some(dimension) => Err(refused(dimension)),
+            None => Ok(()),
+        }
+    }
+
+    /// Refuse a funnel report broken down by or filtered on a withheld
+    /// dimension
+    pub fn check_funnel(&self, request: &RunFunnelReportRequest) -> Result<(), ClientError> {
+        self.check_filter(request.dimension_filter.as_ref())?;
+        let breakdown = request.funnel_breakdown.as_ref().and_then(|b| b.breakdown_dimension.as_ref());
+        match breakdown.into_iter().flat_map(referenced_dimensions).find(|d| self.is_redacted(d)) {
+            Some(dimension) => Err(refused(dimension)),
+            None => Ok(()),
+        }
+    }
+
+    /// Put withheld columns back as "(redacted)", hide rows under the
+    /// minimum users and record what was done in the response metadata
+    pub fn apply(&self, prepared: &PreparedRequest, response: &mut RunReportResponse) {
+        let redactions = self.apply_parts(
+            prepared,
+            ResponseParts {
+                dimension_headers: &mut response.dimension_headers,
+                metric_headers: &mut response.metric_headers,
+                rows: &mut response.rows,
+                aggregates: [&mut response.totals, &mut response.maximums, &mut response.minimums],
+                row_count: &mut response.row_count,
+            },
+        );
+        if redactions.is_empty() {
+            return;
+        }
+
+        let metadata = response.metadata.get_or_insert(ResponseMetaData {
+            data_loss_from_other_row: None,
+            schema_restriction_response: None,
+            currency_code: None,
+            time_zone: None,
+            subject_to_thresholding: None,
+            sampling_metadatas: None,
+            privacy_redactions: None,
+        });
+        let recorded = metadata.privacy_redactions.get_or_insert_with(Vec::new);
+        for redaction in redactions {
+            PrivacyRedaction::record(recorded, redaction);
+        }
+    }
+
+    /// Realtime counterpart of [`apply`](Self::apply); realtime responses
+    /// carry no metadata to record the redactions in
+    pub fn apply_realtime(&self, prepared: &PreparedRequest, response: &mut RunRealtimeReportResponse) {
+        self.apply_parts(
+            prepared,
+            ResponseParts {
+                dimension_headers: &mut response.dimension_headers,
+                metric_headers: &mut response.metric_headers,
+                rows: &mut response.rows,
+                aggregates: [&mut response.totals, &mut response.maximums, &mut response.minimums],
+                row_count: &mut response.row_count,
+            },
+        );
+    }
+
+    fn check_filter(&self, filter: Option<&FilterExpression>) -> Result<(), ClientError> {
+        let mut pending: Vec<&FilterExpression> = filter.into_iter().collect();
+        while let Some(expression) = pending.pop() {
+            for group in [&expression.and_group, &expression.or_group].into_iter().flatten() {
+                pending.extend(group.expressions.iter());
+            }
+            if let Some(not) = &expression.not_expression {
+                pending.push(not);
+            }
+            if let Some(filter) = &expression.filter {
+                if self.is_redacted(&filter.field_name) {
+                    return Err(refused(&filter.field_name));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn prepare_parts(
+        &self,
+        dimensions: &mut Option<Vec<Dimension>>,
+        metrics: &mut Vec<Metric>,
+        order_bys: &mut Option<Vec<OrderBy>>,
+        users_metric: &str,
+    ) -> PreparedRequest {
+        let mut prepared = PreparedRequest::default();
+        if self.is_empty() {
+            return prepared;
+        }
+
+        if let Some(dimensions) = dimensions.as_mut() {
+            let mut index = 0;
+            dimensions.retain(|dimension| {
+                let redacted = referenced_dimensions(dimension).any(|d| self.is_redacted(d));
+                if redacted {
+                    prepared.removed.push((index, dimension.name.clone()));
+                }
+                index += 1;
+                !redacted
+            });
+        }
+        if let Some(order_bys) = order_bys.as_mut() {
+            order_bys.retain(|order| {
+                order.dimension.as_ref().is_none_or(|d| {
+                    prepared.removed.iter().all(|(_, name)| *name != d.dimension_name)
+                })
+            });
+        }
+
+        // Breakdowns keep the shape they were asked for, so the threshold
+        // looks at the dimensions as requested
+        let breakdown = dimensions
+            .iter()
+            .flatten()
+            .map(|d| d.name.as_str())
+            .chain(prepared.removed.iter().map(|(_, name)| name.as_str()))
+            .any(|name| !TIME_DIMENSIONS.contains(&name));
+        if self.min_users > 0 && breakdown {
+            prepared.users_metric = match metrics.iter().position(|m| m.name == users_metric) {
+                Some(index) => Some(index),
+                None => {
+                    metrics.push(Metric { name: users_metric.to_string(), expression: None, invisible: None });
+                    prepared.users_metric_added = true;
+                    Some(metrics.len() - 1)
+                }
+            };
+        }
+
+        prepared
+    }
+
+    fn apply_parts(&self, prepared: &PreparedRequest, mut parts: ResponseParts<'_>) -> Vec<PrivacyRedaction> {
+        let mut redactions = Vec::new();
+
+        if let Some(users_metric) = prepared.users_metric {
+            let min_users = f64::from(self.min_users);
+            let mut rows_hidden = 0;
+            if let Some(rows) = parts.rows.as_mut() {
+                let before = rows.len();
+                rows.retain(|row| {
+                    let users = row
+                        .metric_values
+                        .as_ref()
+                        .and_then(|values| values.get(users_metric))
+                        .and_then(|value| value.value.as_ref())
+                        .and_then(|value| value.parse::<f64>().ok())
+                        .unwrap_or(0.0);
+                    users >= min_users
+                });
+                rows_hidden = (before - rows.len()) as u64;
+            }
+            if let Some(row_count) = parts.row_count.as_mut() {
+                *row_count = (*row_count - rows_hidden as i32).max(0);
+            }
+            redactions.push(PrivacyRedaction::MinimumUsers { min_users: self.min_users, rows_hidden });
+
+            if prepared.users_metric_added {
+                if let Some(headers) = parts.metric_headers.as_mut() {
+                    if users_metric < headers.len() {
+                        headers.remove(users_metric);
+                    }
+                }
+                let strip = |row: &mut Row| {
+                    if let Some(values) = row.metric_values.as_mut() {
+                        if users_metric < values.len() {
+                            values.remove(users_metric);
+                        }
+                    }
+                };
+                parts.rows.iter_mut().flatten().for_each(strip);
+                for rows in parts.aggregates.iter_mut() {
+                    rows.iter_mut().flatten().for_each(strip);
+                }
+            }
+        }
+
+        if !prepared.removed.is_empty() {
+            if let Some(headers) = parts.dimension_headers.as_mut() {
+                for (index, name) in &prepared.removed {
+                    headers.insert((*index).min(headers.len()), DimensionHeader { name: name.clone() });
+                }
+            }
+            for row in parts.rows.iter_mut().flatten() {
+                insert_placeholders(row, &prepared.removed, REDACTED_VALUE);
+            }
+            // Aggregate rows read RESERVED_TOTAL and the like in every column
+            for row in parts.aggregates.into_iter().flat_map(|rows| rows.iter_mut().flatten()) {
+                let label = row
+                    .dimension_values
+                    .as_ref()
+                    .and_then(|values| values.first())
+                    .and_then(|value| value.value.clone())
+                    .filter(|value| value.starts_with("RESERVED_"));
+                insert_placeholders(row, &prepared.removed, label.as_deref().unwrap_or(REDACTED_VALUE));
+            }
+            redactions.extend(
+                prepared
+                    .removed
+                    .iter()
+                    .map(|(_, name)| PrivacyRedaction::Dimension { dimension: name.clone() }),
+            );
+        }
+
+        redactions
+    }
+}
+
+/// Dimensions a requested dimension reads, itself included
+fn referenced_dimensions(dimension: &Dimension) -> impl Iterator<Item = &str> {
+    let expression = dimension.dimension_expression.as_ref();
+    let cases = expression
+        .into_iter()
+        .flat_map(|e| [e.lower_case.as_ref(), e.upper_case.as_ref()])
+        .flatten()
+        .map(|case| case.dimension_name.as_str());
+    let concatenated = expression
+        .and_then(|e| e.concatenate.as_ref())
+        .into_iter()
+        .flat_map(|c| c.dimension_names.iter().map(String::as_str));
+    std::iter::once(dimension.name.as_str()).chain(cases).chain(concatenated)
+}
+
+fn refused(dimension: &str) -> ClientError {
+    ClientError::RequestFailed(format!(
+        "The dimension {} is disabled by the privacy settings",
+        dimension
+    ))
+}
+
+fn insert_placeholders(row: &mut Row, removed: &[(usize, String)], value: &str) {
+    let values = row.dimension_values.get_or_insert_with(Vec::new);
+    for (index, _) in removed {
+        let position = (*index).min(values.len());
+        values.insert(position, DimensionValue { value: Some(value.to_string()), one_value: None });
+    }
+}
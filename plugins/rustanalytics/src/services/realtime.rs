@@ -1,25 +1,93 @@
 //! Real-time Analytics Service
 //!
 //! Service for fetching real-time analytics data from Google Analytics.
+//!
+//! When a first-party source is attached, the realtime overview falls back
+//! to it while GA4 fails or its quota is exhausted, so the dashboard keeps
+//! showing live data during provider incidents.
 
 use std::sync::Arc;
 
-use chrono::Utc;
+use chrono::{DateTime, Duration, Utc};
+use parking_lot::RwLock;
+use tracing::warn;
 
 use crate::models::realtime::*;
 use crate::models::api::*;
 use crate::services::client::{ClientError, GoogleAnalyticsClient};
+use crate::services::first_party::FirstPartySource;
+use crate::services::privacy::REDACTED_VALUE;
+
+/// How long GA4 is left alone after its quota is exhausted, in seconds
+const QUOTA_BACKOFF_SECS: i64 = 300;
 
 /// Real-time Analytics Service
 pub struct RealtimeService {
     /// GA API client
     client: Arc<GoogleAnalyticsClient>,
+    /// First-party data used while GA4 is unavailable
+    fallback: Option<Arc<dyn FirstPartySource>>,
+    /// GA4 is not asked again before this time after quota errors
+    ga_backoff_until: RwLock<Option<DateTime<Utc>>>,
 }
 
 impl RealtimeService {
     /// Create a new realtime service
     pub fn new(client: Arc<GoogleAnalyticsClient>) -> Self {
-        Self { client }
+        Self {
+            client,
+            fallback: None,
+            ga_backoff_until: RwLock::new(None),
+        }
+    }
+
+    /// Fall back to `source` for the realtime overview when GA4 is unavailable
+    pub fn with_fallback(mut self, source: Arc<dyn FirstPartySource>) -> Self {
+        self.fallback = Some(source);
+        self
+    }
+
+    /// Get the real-time overview, tagged with where it came from
+    ///
+    /// GA4 errors are returned as-is when no fallback is attached or the
+    /// fallback fails too.
+    pub async fn get_overview_report(&self) -> Result<RealtimeReport, ClientError> {
+        let Some(fallback) = &self.fallback else {
+            return self.get_overview().await.map(RealtimeReport::ga4);
+        };
+
+        let backing_off = self.ga_backoff_until.read().is_some_and(|until| Utc::now() < until);
+        if backing_off {
+            if let Ok(overview) = fallback.realtime_overview().await {
+                return Ok(RealtimeReport::first_party(
+                    overview,
+                    "Google Analytics quota exhausted".to_string(),
+                ));
+            }
+        }
+
+        let error = match self.get_overview().await {
+            Ok(overview) => {
+                *self.ga_backoff_until.write() = None;
+                return Ok(RealtimeReport::ga4(overview));
+            }
+            Err(e) => e,
+        };
+
+        if let Some(backoff) = backoff_for(&error) {
+            *self.ga_backoff_until.write() = Some(Utc::now() + backoff);
+        }
+
+        match fallback.realtime_overview().await {
+            Ok(overview) => {
+                warn!("RustAnalytics: GA4 realtime unavailable, using first-party data: {}", error);
+                Ok(RealtimeReport::first_party(overview, error.to_string()))
+            }
+            Err(fallback_error) => {
+                warn!("RustAnalytics: First-party realtime fallback failed: {}", fallback_error);
+                Err(error)
+            }
+        }
     }
 
     /// Get real-time overview data
@@ -322,7 +390,10 @@ impl RealtimeService {
             for row in rows {
                 if let (Some(dims), Some(vals)) = (&row.dimension_values, &row.metric_values) {
                     let country = dims.get(0).and_then(|d| d.value.clone()).unwrap_or_default();
-                    let city = dims.get(1).and_then(|d| d.value.clone());
+                    let city = dims
+                        .get(1)
+                        .and_then(|d| d.value.clone())
+                        .filter(|city| city != REDACTED_VALUE);
                     let active_users: u32 = vals
                         .get(0)
                         .and_then(|v| v.value.as_ref())
@@ -617,7 +688,7 @@ impl RealtimeService {
                         });
 
                     // Add city
-                    if !city.is_empty() && city != "(not set)" {
+                    if !city.is_empty() && city != "(not set)" && city != REDACTED_VALUE {
                         cities.push(CityActiveUsers {
                             city: city.clone(),
                             country: country.clone(),
@@ -648,10 +719,38 @@ impl RealtimeService {
     }
 }
 
+/// How long to skip GA4 after `error`, if it signals exhausted quota
+fn backoff_for(error: &ClientError) -> Option<Duration> {
+    match error {
+        ClientError::RateLimited(secs) => Some(Duration::seconds(*secs as i64)),
+        ClientError::QuotaExceeded(_) => Some(Duration::seconds(QUOTA_BACKOFF_SECS)),
+        _ => None,
+    }
+}
+
+impl RealtimeReport {
+    fn ga4(overview: RealtimeOverview) -> Self {
+        Self {
+            overview,
+            source: RealtimeSource::Ga4,
+            fallback_reason: None,
+        }
+    }
+
+    fn first_party(overview: RealtimeOverview, reason: String) -> Self {
+        Self {
+            overview,
+            source: RealtimeSource::FirstParty,
+            fallback_reason: Some(reason),
+        }
+    }
+}
+
 impl std::fmt::Debug for RealtimeService {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("RealtimeService")
             .field("client", &self.client)
+            .field("fallback", &self.fallback.is_some())
             .finish()
     }
 }
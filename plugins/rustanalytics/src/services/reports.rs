@@ -2,17 +2,20 @@
 //!
 //! Service for creating, managing, and running custom analytics reports.
 
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use chrono::Utc;
+use parking_lot::RwLock;
 use tracing::{debug, info};
 use uuid::Uuid;
 
 use crate::models::reports::*;
 use crate::models::api::*;
-use crate::models::{DateRange, DateRangePreset, ReportFormat};
+use crate::models::{DataQuality, DateRange, DateRangePreset, ReportFormat};
 use crate::services::cache::CacheService;
 use crate::services::client::{ClientError, GoogleAnalyticsClient};
+use crate::services::delivery::{self, ReportDelivery, Secret};
 
 /// Database pool type alias
 type DbPool = Arc<dyn std::any::Any + Send + Sync>;
@@ -26,12 +29,29 @@ pub struct ReportService {
     /// Database pool (reserved for future database integration)
     #[allow(dead_code)]
     db: DbPool,
+    /// Scheduled reports by ID
+    schedules: RwLock<HashMap<Uuid, ScheduledReport>>,
+    /// Pushes scheduled results to targets besides email
+    delivery: Option<Arc<ReportDelivery>>,
 }
 
 impl ReportService {
     /// Create a new report service
     pub fn new(client: Arc<GoogleAnalyticsClient>, cache: Arc<CacheService>, db: DbPool) -> Self {
-        Self { client, cache, db }
+        Self {
+            client,
+            cache,
+            db,
+            schedules: RwLock::new(HashMap::new()),
+            delivery: None,
+        }
+    }
+
+    /// Deliver scheduled results to their Sheets, SFTP and S3 targets
+    /// through `delivery`
+    pub fn with_delivery(mut self, delivery: Arc<ReportDelivery>) -> Self {
+        self.delivery = Some(delivery);
+        self
     }
 
     /// List all custom reports
@@ -94,7 +114,7 @@ impl ReportService {
         report: &CustomReport,
         date_range: Option<DateRange>,
     ) -> Result<ReportResult, ClientError> {
-        let date_range = date_range.unwrap_or_else(|| self.date_range_from_preset(&report.date_range));
+        let date_range = date_range.unwrap_or_else(|| report.date_range.date_range());
 
         // Check cache first
         let cache_key = format!(
@@ -241,6 +261,7 @@ impl ReportService {
         response: RunReportResponse,
         date_range: DateRange,
     ) -> Result<ReportResult, ClientError> {
+        let data_quality = DataQuality::from_response(&response);
         let mut rows = Vec::new();
 
         if let Some(response_rows) = response.rows {
@@ -318,7 +339,9 @@ impl ReportService {
             rows,
             totals,
             row_count,
-            sampling_info: None,
+            data_quality_warnings: data_quality.warnings(),
+            redactions: data_quality.redactions,
+            sampling_info: data_quality.sampling,
             generated_at: Utc::now(),
         })
     }
@@ -465,23 +488,6 @@ impl ReportService {
         Ok(html)
     }
 
-    /// Get date range from preset
-    fn date_range_from_preset(&self, preset: &DateRangePreset) -> DateRange {
-        match preset {
-            DateRangePreset::Today => DateRange::today(),
-            DateRangePreset::Yesterday => DateRange::yesterday(),
-            DateRangePreset::Last7Days => DateRange::last_n_days(7),
-            DateRangePreset::Last14Days => DateRange::last_n_days(14),
-            DateRangePreset::Last28Days => DateRange::last_n_days(28),
-            DateRangePreset::Last30Days => DateRange::last_n_days(30),
-            DateRangePreset::Last90Days => DateRange::last_n_days(90),
-            DateRangePreset::Last365Days => DateRange::last_n_days(365),
-            DateRangePreset::ThisMonth => DateRange::this_month(),
-            DateRangePreset::LastMonth => DateRange::last_month(),
-            _ => DateRange::last_n_days(30),
-        }
-    }
-
     /// Get available report templates
     pub fn get_templates(&self) -> Vec<ReportTemplate> {
         vec![
@@ -594,14 +600,30 @@ impl ReportService {
         report_id: Uuid,
         schedule: ScheduledReport,
     ) -> Result<ScheduledReport, ClientError> {
+        let mut targets = schedule.targets.clone();
+        for target in &mut targets {
+            if target.id.is_nil() {
+                target.id = Uuid::new_v4();
+            }
+            delivery::validate_target(target)?;
+        }
+        if !targets.is_empty() && self.delivery.is_none() {
+            return Err(ClientError::InvalidDeliveryTarget(
+                "Report delivery is not configured".to_string(),
+            ));
+        }
+
         let scheduled = ScheduledReport {
             id: Uuid::new_v4(),
             report_id,
             created_at: Utc::now(),
+            targets,
+            deliveries: Vec::new(),
             ..schedule
         };
 
         // Save to database
+        self.schedules.write().insert(scheduled.id, scheduled.clone());
         info!("Scheduled report {} with ID {}", report_id, scheduled.id);
 
         Ok(scheduled)
@@ -610,10 +632,93 @@ impl ReportService {
     /// Get scheduled reports
     pub async fn get_scheduled_reports(
         &self,
-        _report_id: Option<Uuid>,
+        report_id: Option<Uuid>,
     ) -> Result<Vec<ScheduledReport>, ClientError> {
-        // Query database
-        Ok(Vec::new())
+        let mut schedules: Vec<ScheduledReport> = self
+            .schedules
+            .read()
+            .values()
+            .filter(|s| report_id.is_none_or(|id| s.report_id == id))
+            .cloned()
+            .collect();
+        schedules.sort_by_key(|s| s.created_at);
+        Ok(schedules)
+    }
+
+    /// Store the credentials of a schedule's delivery target in the secret
+    /// store, returning the target with its credential reference
+    pub async fn set_delivery_credentials(
+        &self,
+        schedule_id: Uuid,
+        target_id: Uuid,
+        secret: Secret,
+    ) -> Result<DeliveryTarget, ClientError> {
+        let delivery = self.delivery.as_ref().ok_or_else(|| {
+            ClientError::InvalidDeliveryTarget("Report delivery is not configured".to_string())
+        })?;
+        self.delivery_target(schedule_id, target_id)?;
+
+        let credential_id = delivery::credential_id(target_id);
+        delivery.secrets().put(&credential_id, secret).await?;
+
+        let mut schedules = self.schedules.write();
+        let target = schedules
+            .get_mut(&schedule_id)
+            .and_then(|s| s.targets.iter_mut().find(|t| t.id == target_id))
+            .ok_or_else(|| ClientError::InvalidResponse("Delivery target not found".to_string()))?;
+        target.credential_id = Some(credential_id);
+        Ok(target.clone())
+    }
+
+    /// Push a run's results to the schedule's delivery targets, recording
+    /// the outcome per target on the schedule
+    pub async fn deliver_scheduled_report(
+        &self,
+        schedule_id: Uuid,
+        report: &CustomReport,
+        result: &ReportResult,
+    ) -> Result<Vec<DeliveryStatus>, ClientError> {
+        let targets = self.scheduled_report(schedule_id)?.targets;
+        let statuses = match &self.delivery {
+            Some(delivery) => delivery.deliver(report, result, &targets).await,
+            None => Vec::new(),
+        };
+
+        if let Some(schedule) = self.schedules.write().get_mut(&schedule_id) {
+            schedule.last_run = Some(Utc::now());
+            schedule.last_status = Some(if statuses.iter().any(|s| s.state == DeliveryState::Failed) {
+                ReportStatus::Failed
+            } else {
+                ReportStatus::Completed
+            });
+            schedule.deliveries = statuses.clone();
+        }
+
+        Ok(statuses)
+    }
+
+    /// Outcome of the last run's deliveries of a schedule
+    pub async fn get_delivery_status(
+        &self,
+        schedule_id: Uuid,
+    ) -> Result<Vec<DeliveryStatus>, ClientError> {
+        Ok(self.scheduled_report(schedule_id)?.deliveries)
+    }
+
+    fn scheduled_report(&self, schedule_id: Uuid) -> Result<ScheduledReport, ClientError> {
+        self.schedules
+            .read()
+            .get(&schedule_id)
+            .cloned()
+            .ok_or_else(|| ClientError::InvalidResponse("Scheduled report not found".to_string()))
+    }
+
+    fn delivery_target(&self, schedule_id: Uuid, target_id: Uuid) -> Result<DeliveryTarget, ClientError> {
+        self.scheduled_report(schedule_id)?
+            .targets
+            .into_iter()
+            .find(|t| t.id == target_id)
+            .ok_or_else(|| ClientError::InvalidResponse("Delivery target not found".to_string()))
     }
 }
 
@@ -621,6 +726,7 @@ impl std::fmt::Debug for ReportService {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("ReportService")
             .field("client", &self.client)
+            .field("delivery", &self.delivery)
             .finish()
     }
 }
@@ -0,0 +1,203 @@
+//! Overview Snapshot Service
+//!
+//! Keeps a precomputed admin overview so the dashboard does not wait on the
+//! GA queries behind it. A scheduled job builds the snapshot; the overview
+//! handler serves it as is and asks for a background rebuild once it is
+//! older than the configured age.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration, Utc};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info, warn};
+
+use crate::models::{AnalyticsOverview, AnalyticsSettings, DataQuality, DateRangePreset};
+use crate::services::analytics::AnalyticsService;
+use crate::services::client::ClientError;
+
+/// Precomputed admin overview
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OverviewSnapshot {
+    pub overview: AnalyticsOverview,
+    pub data_quality: DataQuality,
+    pub built_at: DateTime<Utc>,
+    pub build_duration_ms: u64,
+}
+
+impl OverviewSnapshot {
+    /// Time since the snapshot was built
+    pub fn age(&self) -> Duration {
+        Utc::now() - self.built_at
+    }
+
+    /// Whether the snapshot is older than `max_age`
+    pub fn is_stale(&self, max_age: Duration) -> bool {
+        self.age() > max_age
+    }
+}
+
+/// State of the snapshot for the dashboard
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotStatus {
+    pub built_at: Option<DateTime<Utc>>,
+    pub is_stale: bool,
+    pub is_refreshing: bool,
+    pub last_error: Option<String>,
+}
+
+/// Overview Snapshot Service for the admin dashboard
+pub struct OverviewSnapshotService {
+    /// Analytics service building the overview
+    analytics: Arc<AnalyticsService>,
+    /// Date range the snapshot covers, resolved on every build
+    date_range: DateRangePreset,
+    /// Whether to build the comparison with the previous period
+    compare: bool,
+    /// Age after which serving the snapshot triggers a rebuild
+    max_age: Duration,
+    /// Latest snapshot
+    snapshot: RwLock<Option<OverviewSnapshot>>,
+    /// Set while a build is running
+    refreshing: AtomicBool,
+    /// Error of the last failed build
+    last_error: RwLock<Option<String>>,
+}
+
+impl OverviewSnapshotService {
+    /// Create a new snapshot service covering the default date range of
+    /// the settings
+    pub fn new(analytics: Arc<AnalyticsService>, settings: &AnalyticsSettings) -> Self {
+        Self {
+            analytics,
+            date_range: settings.default_date_range,
+            compare: settings.comparison_enabled,
+            max_age: Duration::minutes(i64::from(settings.cache_duration_minutes.max(1))),
+            snapshot: RwLock::new(None),
+            refreshing: AtomicBool::new(false),
+            last_error: RwLock::new(None),
+        }
+    }
+
+    /// Rebuild snapshots older than `max_age` when they are served
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    /// Latest snapshot, if one was built
+    pub fn snapshot(&self) -> Option<OverviewSnapshot> {
+        self.snapshot.read().clone()
+    }
+
+    /// Replace the snapshot
+    pub fn store(&self, snapshot: OverviewSnapshot) {
+        *self.snapshot.write() = Some(snapshot);
+        *self.last_error.write() = None;
+    }
+
+    /// Get snapshot status
+    pub fn status(&self) -> SnapshotStatus {
+        let snapshot = self.snapshot.read();
+        SnapshotStatus {
+            built_at: snapshot.as_ref().map(|s| s.built_at),
+            is_stale: snapshot.as_ref().map_or(true, |s| s.is_stale(self.max_age)),
+            is_refreshing: self.is_refreshing(),
+            last_error: self.last_error.read().clone(),
+        }
+    }
+
+    /// Whether a build is running
+    pub fn is_refreshing(&self) -> bool {
+        self.refreshing.load(Ordering::Acquire)
+    }
+
+    /// Snapshot for the overview handler
+    ///
+    /// Returns the stored snapshot right away, starting a background
+    /// rebuild when it is stale. Only the very first request, before any
+    /// snapshot exists, waits for GA.
+    pub async fn serve(self: &Arc<Self>) -> Result<OverviewSnapshot, ClientError> {
+        let Some(snapshot) = self.snapshot() else {
+            return self.refresh().await;
+        };
+
+        if snapshot.is_stale(self.max_age) {
+            self.refresh_in_background();
+        }
+        Ok(snapshot)
+    }
+
+    /// Rebuild the snapshot now, for the scheduled job and the manual
+    /// refresh endpoint
+    ///
+    /// A failed build keeps the previous snapshot.
+    pub async fn refresh(&self) -> Result<OverviewSnapshot, ClientError> {
+        if self.refreshing.swap(true, Ordering::AcqRel) {
+            return Err(ClientError::RequestFailed(
+                "Overview snapshot refresh already in progress".to_string(),
+            ));
+        }
+
+        let result = self.build().await;
+        self.refreshing.store(false, Ordering::Release);
+
+        match result {
+            Ok(snapshot) => {
+                info!(
+                    "Built overview snapshot in {}ms",
+                    snapshot.build_duration_ms
+                );
+                self.store(snapshot.clone());
+                Ok(snapshot)
+            }
+            Err(e) => {
+                warn!("Failed to build overview snapshot: {}", e);
+                *self.last_error.write() = Some(e.to_string());
+                Err(e)
+            }
+        }
+    }
+
+    /// Start a rebuild without waiting for it; returns false when one is
+    /// already running
+    pub fn refresh_in_background(self: &Arc<Self>) -> bool {
+        if self.is_refreshing() {
+            return false;
+        }
+
+        debug!("Refreshing overview snapshot in the background");
+        let service = self.clone();
+        tokio::spawn(async move {
+            let _ = service.refresh().await;
+        });
+        true
+    }
+
+    /// Build the overview from GA, skipping cached reports
+    async fn build(&self) -> Result<OverviewSnapshot, ClientError> {
+        let start = std::time::Instant::now();
+        let report = self
+            .analytics
+            .fetch_overview(self.date_range.date_range(), self.compare)
+            .await?;
+
+        Ok(OverviewSnapshot {
+            overview: report.data,
+            data_quality: report.data_quality,
+            built_at: Utc::now(),
+            build_duration_ms: start.elapsed().as_millis() as u64,
+        })
+    }
+}
+
+impl std::fmt::Debug for OverviewSnapshotService {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OverviewSnapshotService")
+            .field("date_range", &self.date_range)
+            .field("compare", &self.compare)
+            .field("status", &self.status())
+            .finish()
+    }
+}
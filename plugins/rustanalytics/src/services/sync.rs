@@ -11,6 +11,7 @@ use crate::models::DateRange;
 use crate::services::analytics::AnalyticsService;
 use crate::services::cache::CacheService;
 use crate::services::client::{ClientError, GoogleAnalyticsClient};
+use crate::services::snapshot::OverviewSnapshotService;
 
 /// Database pool type alias
 type DbPool = Arc<dyn std::any::Any + Send + Sync>;
@@ -58,6 +59,8 @@ pub struct SyncService {
     db: DbPool,
     /// Current sync status
     status: parking_lot::RwLock<SyncStatus>,
+    /// Overview snapshot rebuilt after every full sync
+    snapshot: Option<Arc<OverviewSnapshotService>>,
 }
 
 impl SyncService {
@@ -81,9 +84,16 @@ impl SyncService {
                 total_syncs: 0,
                 failed_syncs: 0,
             }),
+            snapshot: None,
         }
     }
 
+    /// Rebuild the overview snapshot as part of every full sync
+    pub fn with_snapshot(mut self, snapshot: Arc<OverviewSnapshotService>) -> Self {
+        self.snapshot = Some(snapshot);
+        self
+    }
+
     /// Get current sync status
     pub fn status(&self) -> SyncStatus {
         self.status.read().clone()
@@ -163,6 +173,13 @@ impl SyncService {
             }
         }
 
+        if let Some(snapshot) = &self.snapshot {
+            match snapshot.refresh().await {
+                Ok(_) => records_synced += 1,
+                Err(e) => errors.push(format!("overview snapshot: {}", e)),
+            }
+        }
+
         let duration_ms = start.elapsed().as_millis() as u64;
 
         if errors.is_empty() {
@@ -195,22 +212,22 @@ impl SyncService {
 
         // Sync traffic sources
         if let Ok(sources) = self.analytics.get_traffic_sources(date_range.clone(), Some(100)).await {
-            count += sources.len() as u64;
+            count += sources.data.len() as u64;
         }
 
         // Sync channels
         if let Ok(channels) = self.analytics.get_channels(date_range.clone()).await {
-            count += channels.len() as u64;
+            count += channels.data.len() as u64;
         }
 
         // Sync top pages
         if let Ok(pages) = self.analytics.get_top_pages(date_range.clone(), Some(100)).await {
-            count += pages.len() as u64;
+            count += pages.data.len() as u64;
         }
 
         // Sync referrers
         if let Ok(referrers) = self.analytics.get_referrers(date_range.clone(), Some(100)).await {
-            count += referrers.len() as u64;
+            count += referrers.data.len() as u64;
         }
 
         Ok(count)
@@ -229,7 +246,7 @@ impl SyncService {
         }
 
         if let Ok(pages) = self.analytics.get_top_pages(date_range, Some(10)).await {
-            records_synced += pages.len() as u64;
+            records_synced += pages.data.len() as u64;
         }
 
         let duration_ms = start.elapsed().as_millis() as u64;
@@ -0,0 +1,24 @@
+//! Core Web Vitals Service
+//!
+//! LCP, CLS and INP are measured by RustPress itself: the host collects
+//! beacons from visitors' browsers, rolls them up with percentiles and
+//! checks them against performance budgets, and hands the plugin a
+//! [`WebVitalsSource`] so site speed reports can show field data next to
+//! Google Analytics timings.
+
+use async_trait::async_trait;
+
+use crate::models::behavior::WebVitalsOverview;
+use crate::models::DateRange;
+use crate::services::client::ClientError;
+
+/// Source of Core Web Vitals field data, provided by the host
+#[async_trait]
+pub trait WebVitalsSource: Send + Sync {
+    /// p75 and ratings per metric, device, template and page over a date
+    /// range, with the budgets currently exceeded
+    async fn web_vitals_overview(
+        &self,
+        date_range: &DateRange,
+    ) -> Result<WebVitalsOverview, ClientError>;
+}
@@ -17,7 +17,7 @@ use rustanalytics::models::analytics::{
     SiteSearchData,
 };
 use rustanalytics::models::{
-    AnalyticsOverview, ChannelData, DateRange, DailyMetrics,
+    AnalyticsOverview, ChannelData, DataQuality, DateRange, DailyMetrics,
     OverviewMetrics, PageData, ReferrerData, TrafficSource,
     MetricsComparison, CampaignData, KeywordData, EventData,
     SiteSpeedData, PageTimingData,
@@ -2188,6 +2188,105 @@ fn test_sampling_info_not_sampled() {
     assert!(sampling.samples_read_counts.is_none());
 }
 
+#[test]
+fn test_sampling_info_from_metadata() {
+    let metadata = vec![
+        SamplingMetadata {
+            samples_read_count: Some("250".to_string()),
+            sampling_space_size: Some("1000".to_string()),
+        },
+        SamplingMetadata {
+            samples_read_count: Some("250".to_string()),
+            sampling_space_size: Some("1000".to_string()),
+        },
+    ];
+    let sampling = SamplingInfo::from_metadata(&metadata).unwrap();
+
+    assert!(sampling.is_sampled);
+    assert_eq!(sampling.samples_read_counts, Some(500));
+    assert_eq!(sampling.sampling_space_sizes, Some(2000));
+    assert_eq!(sampling.sample_ratio(), Some(0.25));
+    assert!(SamplingInfo::from_metadata(&[]).is_none());
+}
+
+// ============================================================================
+// Data Quality Tests
+// ============================================================================
+
+#[test]
+fn test_data_quality_from_sampled_response() {
+    let response: RunReportResponse = serde_json::from_value(json!({
+        "rows": [],
+        "metadata": {
+            "subjectToThresholding": true,
+            "samplingMetadatas": [
+                { "samplesReadCount": "250", "samplingSpaceSize": "1000" }
+            ]
+        }
+    }))
+    .unwrap();
+    let quality = DataQuality::from_response(&response);
+
+    assert!(quality.is_sampled());
+    assert!(quality.thresholded);
+    assert!(!quality.data_loss_from_other_row);
+    assert!(!quality.is_exact());
+    assert_eq!(quality.split_requests, 1);
+
+    let warnings = quality.warnings();
+    assert_eq!(warnings.len(), 2);
+    assert!(warnings[0].contains("25.0%"));
+}
+
+#[test]
+fn test_data_quality_exact_response() {
+    let quality = DataQuality::from_response(&sample_overview_response());
+
+    assert!(quality.is_exact());
+    assert!(quality.sampling.is_none());
+    assert!(quality.warnings().is_empty());
+}
+
+#[test]
+fn test_data_quality_merge() {
+    let mut quality = DataQuality::default();
+    quality.merge(DataQuality {
+        data_loss_from_other_row: true,
+        split_requests: 1,
+        ..Default::default()
+    });
+    quality.merge(DataQuality {
+        sampling: SamplingInfo::from_metadata(&[SamplingMetadata {
+            samples_read_count: Some("10".to_string()),
+            sampling_space_size: Some("20".to_string()),
+        }]),
+        split_requests: 1,
+        ..Default::default()
+    });
+
+    assert!(quality.is_sampled());
+    assert!(quality.data_loss_from_other_row);
+    assert_eq!(quality.split_requests, 2);
+    assert!(quality.warnings().iter().any(|w| w.contains("combine 2 requests")));
+}
+
+#[test]
+fn test_date_range_split() {
+    let range = DateRange::new(
+        NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+        NaiveDate::from_ymd_opt(2024, 3, 31).unwrap(),
+    );
+    let chunks = range.split(30);
+
+    assert_eq!(range.days(), 91);
+    assert_eq!(chunks.len(), 4);
+    assert_eq!(chunks[0].end_date, NaiveDate::from_ymd_opt(2024, 1, 30).unwrap());
+    assert_eq!(chunks[3].start_date, NaiveDate::from_ymd_opt(2024, 3, 31).unwrap());
+    assert_eq!(chunks[3].end_date, range.end_date);
+    assert_eq!(chunks.iter().map(DateRange::days).sum::<i64>(), range.days());
+    assert_eq!(range.split(365).len(), 1);
+}
+
 // ============================================================================
 // Clone and Debug Tests
 // ============================================================================
@@ -2360,3 +2459,32 @@ fn test_large_row_count_response() {
         assert!((referrer.percentage - 1.0).abs() < 0.01);
     }
 }
+
+// ============================================================================
+// Period Comparison Tests
+// ============================================================================
+
+#[test]
+fn test_metrics_comparison_between_periods() {
+    let current = OverviewMetrics {
+        sessions: 150,
+        users: 100,
+        revenue: 0.0,
+        bounce_rate: 40.0,
+        ..OverviewMetrics::default()
+    };
+    let previous = OverviewMetrics {
+        sessions: 100,
+        users: 0,
+        revenue: 0.0,
+        bounce_rate: 50.0,
+        ..OverviewMetrics::default()
+    };
+
+    let comparison = MetricsComparison::between(&current, &previous);
+
+    assert!((comparison.sessions_change - 50.0).abs() < 1e-9);
+    assert!((comparison.bounce_rate_change + 20.0).abs() < 1e-9);
+    assert_eq!(comparison.users_change, 100.0);
+    assert_eq!(comparison.revenue_change, 0.0);
+}
@@ -1941,3 +1941,111 @@ fn test_zero_values() {
     let json = serde_json::to_string(&comparison).unwrap();
     assert!(json.contains(":0"));
 }
+
+#[test]
+fn test_podcast_downloads_percentages() {
+    let overview = PodcastDownloadsOverview {
+        date_range: sample_date_range(),
+        downloads: 200,
+        requests: 950,
+        bytes_served: 4_800_000_000,
+        downloads_trend: vec![DownloadsTrendData {
+            date: NaiveDate::from_ymd_opt(2024, 1, 15).unwrap(),
+            downloads: 40,
+        }],
+        top_episodes: vec![EpisodeDownloadData {
+            post_id: "8f14e45f-ceea-467f-a0e6-7a1c2b3d4e5f".to_string(),
+            episode_title: "Episode 1".to_string(),
+            page_path: "/post/episode-1".to_string(),
+            downloads: 150,
+            requests: 700,
+            bytes_served: 3_600_000_000,
+            percentage: 0.0,
+        }],
+        podcast_apps: vec![PodcastAppData {
+            app: "Apple Podcasts".to_string(),
+            downloads: 50,
+            percentage: 0.0,
+        }],
+    }
+    .with_percentages();
+
+    assert_eq!(overview.top_episodes[0].percentage, 75.0);
+    assert_eq!(overview.podcast_apps[0].percentage, 25.0);
+
+    let json = serde_json::to_string(&overview).unwrap();
+    let parsed: PodcastDownloadsOverview = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed.bytes_served, 4_800_000_000);
+    assert_eq!(parsed.downloads_trend[0].downloads, 40);
+
+    let empty = PodcastDownloadsOverview {
+        downloads: 0,
+        ..parsed
+    }
+    .with_percentages();
+    assert_eq!(empty.top_episodes[0].percentage, 0.0);
+}
+
+#[test]
+fn test_web_vitals_percentages() {
+    let vital = |metric: &str, good: u64, needs_improvement: u64, poor: u64| WebVitalData {
+        metric: metric.to_string(),
+        samples: good + needs_improvement + poor,
+        p75: Some(2_100.0),
+        rating: Some("good".to_string()),
+        good,
+        needs_improvement,
+        poor,
+        good_percentage: 0.0,
+        needs_improvement_percentage: 0.0,
+        poor_percentage: 0.0,
+    };
+    let overview = WebVitalsOverview {
+        date_range: sample_date_range(),
+        metrics: vec![vital("LCP", 75, 15, 10)],
+        device_breakdown: vec![DeviceVitalsData {
+            device: "mobile".to_string(),
+            metrics: vec![vital("INP", 1, 0, 3)],
+        }],
+        template_breakdown: vec![TemplateVitalsData {
+            template: "post".to_string(),
+            metrics: vec![vital("CLS", 0, 0, 0)],
+        }],
+        top_pages: vec![PageVitalsData {
+            page_path: "/post/hello".to_string(),
+            template: "post".to_string(),
+            metrics: vec![vital("LCP", 1, 1, 0)],
+        }],
+        vitals_trend: vec![VitalsTrendData {
+            date: NaiveDate::from_ymd_opt(2024, 1, 15).unwrap(),
+            lcp_p75: Some(2_100.0),
+            cls_p75: Some(0.05),
+            inp_p75: None,
+        }],
+        budget_alerts: vec![VitalsBudgetAlert {
+            template: "post".to_string(),
+            device: Some("mobile".to_string()),
+            metric: "INP".to_string(),
+            budget: 200.0,
+            p75: 340.0,
+            baseline_p75: Some(180.0),
+            reason: "over_budget".to_string(),
+            opened_at: chrono::Utc::now(),
+        }],
+    }
+    .with_percentages();
+
+    assert_eq!(overview.metrics[0].good_percentage, 75.0);
+    assert_eq!(overview.metrics[0].poor_percentage, 10.0);
+    assert_eq!(overview.device_breakdown[0].metrics[0].poor_percentage, 75.0);
+    assert_eq!(overview.template_breakdown[0].metrics[0].good_percentage, 0.0);
+    assert_eq!(
+        overview.top_pages[0].metrics[0].needs_improvement_percentage,
+        50.0
+    );
+
+    let json = serde_json::to_string(&overview).unwrap();
+    let parsed: WebVitalsOverview = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed.vitals_trend[0].inp_p75, None);
+    assert_eq!(parsed.budget_alerts[0].p75, 340.0);
+}
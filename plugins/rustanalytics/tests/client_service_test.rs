@@ -6,6 +6,7 @@
 //! - Date range building and formatting
 //! - API model structures and serialization
 //! - Filter expressions construction
+//! - Access token lifecycle and token store
 
 use chrono::NaiveDate;
 use serde_json;
@@ -1163,6 +1164,9 @@ mod run_report_response_tests {
                 schema_restriction_response: None,
                 currency_code: Some("USD".to_string()),
                 time_zone: Some("America/Los_Angeles".to_string()),
+                subject_to_thresholding: None,
+                sampling_metadatas: None,
+                privacy_redactions: None,
             }),
             property_quota: None,
             kind: None,
@@ -1705,6 +1709,127 @@ mod token_response_tests {
     }
 }
 
+// ============================================================================
+// Token Lifecycle Tests
+// ============================================================================
+
+mod token_lifecycle_tests {
+    use super::*;
+    use chrono::{Duration, TimeZone, Utc};
+    use rustanalytics::models::TokenState;
+    use rustanalytics::services::client::{MemoryTokenStore, StoredToken, TokenStore};
+    use rustpress_core::http::HttpClient;
+    use std::sync::Arc;
+
+    fn issued_at() -> chrono::DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 15, 12, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_refresh_scheduled_before_expiry() {
+        let token = StoredToken::issue("key-1", "ya29.abc", issued_at(), 3600, 0);
+        assert_eq!(token.expires_at, issued_at() + Duration::seconds(3600));
+        assert_eq!(token.refresh_at, token.expires_at - Duration::seconds(300));
+    }
+
+    #[test]
+    fn test_refresh_jitter_moves_refresh_earlier() {
+        let token = StoredToken::issue("key-1", "ya29.abc", issued_at(), 3600, 90);
+        assert_eq!(token.refresh_at, token.expires_at - Duration::seconds(390));
+    }
+
+    #[test]
+    fn test_short_lived_token_refreshes_halfway() {
+        let token = StoredToken::issue("key-1", "ya29.abc", issued_at(), 400, 120);
+        assert_eq!(token.refresh_at, issued_at() + Duration::seconds(200));
+    }
+
+    #[test]
+    fn test_missing_lifetime_uses_default() {
+        let token = StoredToken::issue("key-1", "ya29.abc", issued_at(), 0, 0);
+        assert_eq!(token.expires_at, issued_at() + Duration::seconds(3600));
+    }
+
+    #[test]
+    fn test_refresh_due_before_expired() {
+        let token = StoredToken::issue("key-1", "ya29.abc", issued_at(), 3600, 0);
+        let due = token.refresh_at + Duration::seconds(1);
+        assert!(token.needs_refresh(due));
+        assert!(!token.is_expired(due));
+        assert!(token.is_expired(token.expires_at));
+        assert!(!token.needs_refresh(issued_at()));
+    }
+
+    #[tokio::test]
+    async fn test_memory_store_keeps_latest_token_per_key() {
+        let store = MemoryTokenStore::new();
+        store.save(&StoredToken::issue("key-1", "first", issued_at(), 3600, 0)).await.unwrap();
+        store.save(&StoredToken::issue("key-1", "second", issued_at(), 3600, 0)).await.unwrap();
+
+        let loaded = store.load("key-1").await.unwrap().unwrap();
+        assert_eq!(loaded.access_token, "second");
+        assert!(store.load("key-2").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_memory_store_purges_expired_tokens() {
+        let store = MemoryTokenStore::new();
+        store.save(&StoredToken::issue("key-1", "short", issued_at(), 600, 0)).await.unwrap();
+        store.save(&StoredToken::issue("key-2", "long", issued_at(), 3600, 0)).await.unwrap();
+
+        let purged = store.purge_expired(issued_at() + Duration::seconds(1200)).await.unwrap();
+        assert_eq!(purged, 1);
+        assert!(store.load("key-1").await.unwrap().is_none());
+        assert!(store.load("key-2").await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_token_health_without_credentials() {
+        let client = GoogleAnalyticsClient::with_http_client(
+            HttpClient::default(),
+            "properties/123".to_string(),
+            None,
+        )
+        .await
+        .unwrap();
+
+        let health = client.token_health();
+        assert_eq!(health.state, TokenState::Missing);
+        assert!(health.key_id.is_none());
+        assert!(health.previous_key_id.is_none());
+        assert_eq!(health.consecutive_failures, 0);
+    }
+
+    #[tokio::test]
+    async fn test_maintenance_without_credentials_is_noop() {
+        let client = GoogleAnalyticsClient::with_token_store(
+            HttpClient::default(),
+            "properties/123".to_string(),
+            None,
+            Arc::new(MemoryTokenStore::new()),
+        )
+        .await
+        .unwrap();
+
+        assert!(client.maintain_tokens().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_rotate_key_rejects_invalid_credentials() {
+        let client = GoogleAnalyticsClient::with_http_client(
+            HttpClient::default(),
+            "properties/123".to_string(),
+            None,
+        )
+        .await
+        .unwrap();
+
+        let result = client.rotate_key("not json", Duration::minutes(60)).await;
+        assert!(matches!(result, Err(ClientError::InvalidCredentials(_))));
+        assert_eq!(client.token_health().state, TokenState::Missing);
+    }
+}
+
 // ============================================================================
 // API Error Tests
 // ============================================================================
@@ -2264,6 +2389,9 @@ mod response_metadata_tests {
             }),
             currency_code: Some("EUR".to_string()),
             time_zone: Some("Europe/Berlin".to_string()),
+            subject_to_thresholding: None,
+            sampling_metadatas: None,
+            privacy_redactions: None,
         };
         assert_eq!(metadata.data_loss_from_other_row, Some(true));
         assert_eq!(metadata.currency_code, Some("EUR".to_string()));
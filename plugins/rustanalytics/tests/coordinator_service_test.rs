@@ -0,0 +1,268 @@
+//! Request Coordinator Tests
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use rustanalytics::models::api::*;
+use rustanalytics::services::client::ClientError;
+use rustanalytics::services::coordinator::{
+    RequestCoordinator, RequestLimits, RequestPriority, MAX_BATCH_SIZE,
+};
+use tokio::task::JoinSet;
+
+// ============================================================================
+// Helper Functions
+// ============================================================================
+
+fn coordinator(max_concurrent: usize, batch_window_ms: u64) -> Arc<RequestCoordinator> {
+    Arc::new(RequestCoordinator::new(RequestLimits {
+        max_concurrent,
+        max_batch_size: MAX_BATCH_SIZE,
+        batch_window: Duration::from_millis(batch_window_ms),
+    }))
+}
+
+/// Report request tagged with `id` through its limit
+fn report_request(id: i64) -> RunReportRequest {
+    RunReportRequest {
+        property: "properties/12345".to_string(),
+        date_ranges: vec![],
+        dimensions: None,
+        metrics: vec![],
+        dimension_filter: None,
+        metric_filter: None,
+        order_bys: None,
+        offset: None,
+        limit: Some(id),
+        metric_aggregations: None,
+        keep_empty_rows: None,
+        return_property_quota: None,
+    }
+}
+
+/// Response echoing the id of `request` as its row count
+fn echo(request: &RunReportRequest) -> RunReportResponse {
+    RunReportResponse {
+        dimension_headers: None,
+        metric_headers: None,
+        rows: None,
+        totals: None,
+        maximums: None,
+        minimums: None,
+        row_count: request.limit.map(|id| id as i32),
+        metadata: None,
+        property_quota: None,
+        kind: None,
+    }
+}
+
+// ============================================================================
+// Concurrency Limit Tests
+// ============================================================================
+
+#[test]
+fn test_limits_are_normalized() {
+    let coordinator = RequestCoordinator::new(RequestLimits {
+        max_concurrent: 0,
+        max_batch_size: 20,
+        batch_window: Duration::ZERO,
+    });
+
+    assert_eq!(coordinator.limits().max_concurrent, 1);
+    assert_eq!(coordinator.limits().max_batch_size, MAX_BATCH_SIZE);
+}
+
+#[tokio::test]
+async fn test_concurrent_requests_are_limited() {
+    let coordinator = coordinator(2, 0);
+    let running = Arc::new(AtomicUsize::new(0));
+    let peak = Arc::new(AtomicUsize::new(0));
+
+    let mut tasks = JoinSet::new();
+    for _ in 0..6 {
+        let coordinator = coordinator.clone();
+        let running = running.clone();
+        let peak = peak.clone();
+        tasks.spawn(async move {
+            let _permit = coordinator.acquire(RequestPriority::Normal).await;
+            let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+            peak.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            running.fetch_sub(1, Ordering::SeqCst);
+        });
+    }
+    while tasks.join_next().await.is_some() {}
+
+    assert_eq!(peak.load(Ordering::SeqCst), 2);
+    assert_eq!(coordinator.in_flight(), 0);
+}
+
+#[tokio::test]
+async fn test_waiting_requests_start_by_priority() {
+    let coordinator = coordinator(1, 0);
+    let order = Arc::new(Mutex::new(Vec::new()));
+    let held = coordinator.acquire(RequestPriority::Normal).await;
+
+    let mut tasks = JoinSet::new();
+    for priority in [RequestPriority::Low, RequestPriority::Critical, RequestPriority::Normal] {
+        let coordinator = coordinator.clone();
+        let order = order.clone();
+        tasks.spawn(async move {
+            let _permit = coordinator.acquire(priority).await;
+            order.lock().push(priority);
+        });
+        // Let the request join the queue before the next one
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+
+    drop(held);
+    while tasks.join_next().await.is_some() {}
+
+    assert_eq!(
+        *order.lock(),
+        vec![RequestPriority::Critical, RequestPriority::Normal, RequestPriority::Low]
+    );
+}
+
+#[tokio::test]
+async fn test_abandoned_wait_frees_its_place() {
+    let coordinator = coordinator(1, 0);
+    let held = coordinator.acquire(RequestPriority::Normal).await;
+
+    let abandoned = tokio::time::timeout(
+        Duration::from_millis(10),
+        coordinator.acquire(RequestPriority::Critical),
+    )
+    .await;
+    assert!(abandoned.is_err());
+
+    drop(held);
+    let permit = tokio::time::timeout(
+        Duration::from_millis(100),
+        coordinator.acquire(RequestPriority::Low),
+    )
+    .await;
+    assert!(permit.is_ok());
+}
+
+// ============================================================================
+// Report Batching Tests
+// ============================================================================
+
+#[tokio::test]
+async fn test_single_report_is_sent_alone() {
+    let coordinator = coordinator(2, 0);
+
+    let response = coordinator
+        .run_report(RequestPriority::Normal, report_request(7), |requests| async move {
+            assert_eq!(requests.len(), 1);
+            Ok(requests.iter().map(echo).collect())
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(response.row_count, Some(7));
+}
+
+#[tokio::test]
+async fn test_waiting_reports_are_batched() {
+    let coordinator = coordinator(1, 30);
+    let batches = Arc::new(Mutex::new(Vec::new()));
+
+    let mut tasks = JoinSet::new();
+    for id in 0..3 {
+        let coordinator = coordinator.clone();
+        let batches = batches.clone();
+        tasks.spawn(async move {
+            let response = coordinator
+                .run_report(RequestPriority::Normal, report_request(id), |requests| {
+                    batches.lock().push(requests.len());
+                    async move { Ok(requests.iter().map(echo).collect()) }
+                })
+                .await
+                .unwrap();
+            assert_eq!(response.row_count, Some(id as i32));
+        });
+    }
+    while let Some(result) = tasks.join_next().await {
+        result.unwrap();
+    }
+
+    assert_eq!(*batches.lock(), vec![3]);
+}
+
+#[tokio::test]
+async fn test_batches_respect_max_batch_size() {
+    let coordinator = coordinator(1, 30);
+    let batches = Arc::new(Mutex::new(Vec::new()));
+
+    let mut tasks = JoinSet::new();
+    for id in 0..7 {
+        let coordinator = coordinator.clone();
+        let batches = batches.clone();
+        tasks.spawn(async move {
+            coordinator
+                .run_report(RequestPriority::Normal, report_request(id), |requests| {
+                    batches.lock().push(requests.len());
+                    async move { Ok(requests.iter().map(echo).collect()) }
+                })
+                .await
+        });
+    }
+    while let Some(result) = tasks.join_next().await {
+        assert!(result.unwrap().is_ok());
+    }
+
+    let batches = batches.lock();
+    assert!(batches.iter().all(|size| *size <= MAX_BATCH_SIZE));
+    assert_eq!(batches.iter().sum::<usize>(), 7);
+}
+
+#[tokio::test]
+async fn test_batch_error_reaches_every_report() {
+    let coordinator = coordinator(1, 30);
+
+    let mut tasks = JoinSet::new();
+    for id in 0..3 {
+        let coordinator = coordinator.clone();
+        tasks.spawn(async move {
+            coordinator
+                .run_report(RequestPriority::Normal, report_request(id), |_| async {
+                    Err(ClientError::RateLimited(30))
+                })
+                .await
+        });
+    }
+    while let Some(result) = tasks.join_next().await {
+        assert!(matches!(result.unwrap(), Err(ClientError::RateLimited(30))));
+    }
+}
+
+#[tokio::test]
+async fn test_short_batch_response_is_invalid() {
+    let coordinator = coordinator(1, 0);
+
+    let result = coordinator
+        .run_report(RequestPriority::Normal, report_request(1), |_| async { Ok(vec![]) })
+        .await;
+
+    assert!(matches!(result, Err(ClientError::InvalidResponse(_))));
+}
+
+#[test]
+fn test_priority_ordering() {
+    assert!(RequestPriority::Critical > RequestPriority::High);
+    assert!(RequestPriority::High > RequestPriority::Normal);
+    assert!(RequestPriority::Normal > RequestPriority::Low);
+    assert_eq!(RequestPriority::default(), RequestPriority::Normal);
+}
+
+#[test]
+fn test_priority_serialization() {
+    assert_eq!(
+        serde_json::to_string(&RequestPriority::Critical).unwrap(),
+        "\"critical\""
+    );
+}
@@ -0,0 +1,272 @@
+//! Report Delivery Tests
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use parking_lot::Mutex;
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use rustpress_core::http::HttpClient;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use rustanalytics::models::reports::*;
+use rustanalytics::models::{DateRange, DateRangePreset};
+use rustanalytics::services::client::ClientError;
+use rustanalytics::services::delivery::{
+    credential_id, validate_target, DeliveryFile, DeliveryTransport, MemorySecretStore,
+    ReportDelivery, ReportTable, Secret, SecretStore,
+};
+
+// ============================================================================
+// Helper Functions
+// ============================================================================
+
+fn sample_report() -> CustomReport {
+    let now = Utc::now();
+    CustomReport {
+        id: Uuid::new_v4(),
+        name: "Traffic by Page".to_string(),
+        description: None,
+        metrics: vec![ReportMetric {
+            id: "sessions".to_string(),
+            name: "Sessions".to_string(),
+            category: MetricCategory::Session,
+            data_type: MetricDataType::Integer,
+            aggregation: MetricAggregation::Total,
+        }],
+        dimensions: vec![ReportDimension {
+            id: "pageTitle".to_string(),
+            name: "Page Title".to_string(),
+            category: DimensionCategory::PageTracking,
+        }],
+        filters: vec![],
+        segments: vec![],
+        date_range: DateRangePreset::Last7Days,
+        chart_type: ChartType::Table,
+        created_at: now,
+        updated_at: now,
+        created_by: Uuid::new_v4(),
+        is_public: false,
+        is_favorite: false,
+    }
+}
+
+fn row(page: &str, sessions: Value) -> ReportRow {
+    ReportRow {
+        dimensions: vec![page.to_string()],
+        metrics: vec![ReportMetricValue {
+            metric_id: "sessions".to_string(),
+            formatted_value: sessions.to_string(),
+            value: sessions,
+        }],
+    }
+}
+
+fn sample_result() -> ReportResult {
+    ReportResult {
+        report_id: Uuid::new_v4(),
+        date_range: DateRange::last_n_days(7),
+        rows: vec![
+            row("Home", json!("1200")),
+            row("Pricing, \"Pro\" plan", json!("33.5")),
+            row("Unknown", json!("(not set)")),
+        ],
+        totals: None,
+        row_count: 3,
+        sampling_info: None,
+        data_quality_warnings: Vec::new(),
+        redactions: Vec::new(),
+        generated_at: Utc::now(),
+    }
+}
+
+fn s3_target(format: DeliveryFormat) -> DeliveryTarget {
+    DeliveryTarget {
+        id: Uuid::new_v4(),
+        name: "Warehouse".to_string(),
+        destination: DeliveryDestination::S3 {
+            bucket_url: "https://reports.s3.amazonaws.com".to_string(),
+            region: "us-east-1".to_string(),
+            prefix: "analytics/".to_string(),
+        },
+        format,
+        credential_id: None,
+        enabled: true,
+    }
+}
+
+/// Transport recording what it was asked to send
+#[derive(Default)]
+struct RecordingTransport {
+    sent: Mutex<Vec<(String, usize, Option<String>)>>,
+}
+
+#[async_trait]
+impl DeliveryTransport for RecordingTransport {
+    async fn send(
+        &self,
+        _destination: &DeliveryDestination,
+        file: &DeliveryFile,
+        secret: &Secret,
+    ) -> Result<String, ClientError> {
+        self.sent.lock().push((
+            file.file_name.clone(),
+            file.data.len(),
+            secret.get("access_key_id").map(str::to_string),
+        ));
+        Ok(format!("s3://reports/{}", file.file_name))
+    }
+}
+
+// ============================================================================
+// Rendering Tests
+// ============================================================================
+
+#[test]
+fn test_table_parses_metric_values() {
+    let table = ReportTable::from_result(&sample_report(), &sample_result());
+
+    assert_eq!(table.columns, vec!["Page Title", "Sessions"]);
+    assert_eq!(table.dimension_count, 1);
+    assert_eq!(table.rows[0], vec![json!("Home"), json!(1200)]);
+    assert_eq!(table.rows[1][1], json!(33.5));
+    assert_eq!(table.rows[2][1], json!("(not set)"));
+}
+
+#[test]
+fn test_csv_quotes_fields() {
+    let csv = ReportTable::from_result(&sample_report(), &sample_result()).to_csv();
+    let lines: Vec<&str> = csv.split("\r\n").collect();
+
+    assert_eq!(lines[0], "Page Title,Sessions");
+    assert_eq!(lines[1], "Home,1200");
+    assert_eq!(lines[2], "\"Pricing, \"\"Pro\"\" plan\",33.5");
+}
+
+#[test]
+fn test_parquet_round_trip() {
+    let file = DeliveryFile::render(&sample_report(), &sample_result(), DeliveryFormat::Parquet).unwrap();
+    assert!(file.file_name.ends_with(".parquet"));
+    assert!(file.data.starts_with(b"PAR1") && file.data.ends_with(b"PAR1"));
+
+    let path = std::env::temp_dir().join(format!("{}.parquet", Uuid::new_v4()));
+    std::fs::write(&path, &file.data).unwrap();
+    let reader = SerializedFileReader::new(std::fs::File::open(&path).unwrap()).unwrap();
+    let metadata = reader.metadata().file_metadata();
+    let columns: Vec<&str> = metadata.schema_descr().columns().iter().map(|c| c.name()).collect();
+    assert_eq!(columns, vec!["Page Title", "Sessions"]);
+    assert_eq!(metadata.num_rows(), 3);
+
+    let rows: Vec<String> = reader
+        .get_row_iter(None)
+        .unwrap()
+        .map(|row| row.unwrap().to_string())
+        .collect();
+    std::fs::remove_file(&path).unwrap();
+    assert!(rows[0].contains("Home") && rows[0].contains("1200"));
+    assert!(rows[2].contains("null"), "unparseable metric is stored as null");
+}
+
+// ============================================================================
+// Target Tests
+// ============================================================================
+
+#[test]
+fn test_destination_serialization() {
+    let destination: DeliveryDestination = serde_json::from_value(json!({
+        "type": "sftp",
+        "host": "drop.example.com",
+        "username": "reports",
+        "directory": "/incoming",
+        "host_key_fingerprint": "SHA256:abc"
+    }))
+    .unwrap();
+
+    assert_eq!(destination.kind(), DeliveryKind::Sftp);
+    assert!(matches!(destination, DeliveryDestination::Sftp { port: 22, .. }));
+}
+
+#[test]
+fn test_validate_target() {
+    assert!(validate_target(&s3_target(DeliveryFormat::Parquet)).is_ok());
+
+    let mut sheet = s3_target(DeliveryFormat::Parquet);
+    sheet.destination = DeliveryDestination::GoogleSheet {
+        spreadsheet_id: "sheet-id".to_string(),
+        sheet_name: "Weekly".to_string(),
+        append: false,
+    };
+    assert!(matches!(validate_target(&sheet), Err(ClientError::InvalidDeliveryTarget(_))));
+    sheet.format = DeliveryFormat::Csv;
+    assert!(validate_target(&sheet).is_ok());
+
+    let mut sftp = s3_target(DeliveryFormat::Csv);
+    sftp.destination = DeliveryDestination::Sftp {
+        host: "drop.example.com".to_string(),
+        port: 22,
+        username: "reports".to_string(),
+        directory: "incoming".to_string(),
+        host_key_fingerprint: "SHA256:abc".to_string(),
+    };
+    assert!(validate_target(&sftp).is_err(), "relative directory");
+
+    let mut s3 = s3_target(DeliveryFormat::Csv);
+    s3.destination = DeliveryDestination::S3 {
+        bucket_url: "not a url".to_string(),
+        region: "us-east-1".to_string(),
+        prefix: String::new(),
+    };
+    assert!(validate_target(&s3).is_err());
+}
+
+#[test]
+fn test_secret_debug_hides_values() {
+    let secret = Secret::new().with("secret_access_key", "hunter2");
+    let debug = format!("{:?}", secret);
+
+    assert!(debug.contains("secret_access_key"));
+    assert!(!debug.contains("hunter2"));
+}
+
+// ============================================================================
+// Delivery Tests
+// ============================================================================
+
+#[tokio::test]
+async fn test_deliver_to_targets() {
+    let secrets = Arc::new(MemorySecretStore::new());
+    let transport = Arc::new(RecordingTransport::default());
+    let delivery = ReportDelivery::new(HttpClient::default(), secrets.clone())
+        .with_transport(DeliveryKind::S3, transport.clone());
+
+    let mut delivered = s3_target(DeliveryFormat::Csv);
+    delivered.credential_id = Some(credential_id(delivered.id));
+    secrets
+        .put(&credential_id(delivered.id), Secret::new().with("access_key_id", "AKIA"))
+        .await
+        .unwrap();
+    let missing_credentials = s3_target(DeliveryFormat::Csv);
+    let mut disabled = s3_target(DeliveryFormat::Csv);
+    disabled.enabled = false;
+
+    let statuses = delivery
+        .deliver(
+            &sample_report(),
+            &sample_result(),
+            &[delivered.clone(), missing_credentials.clone(), disabled.clone()],
+        )
+        .await;
+
+    assert_eq!(statuses[0].target_id, delivered.id);
+    assert_eq!(statuses[0].state, DeliveryState::Delivered);
+    assert!(statuses[0].location.as_deref().unwrap().starts_with("s3://reports/traffic_by_page_"));
+    assert!(statuses[0].size_bytes > 0);
+    assert_eq!(statuses[1].state, DeliveryState::Failed);
+    assert!(statuses[1].error.as_deref().unwrap().contains("credentials"));
+    assert_eq!(statuses[2].state, DeliveryState::Skipped);
+
+    let sent = transport.sent.lock();
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].2.as_deref(), Some("AKIA"));
+}
@@ -0,0 +1,386 @@
+//! Demo Data Tests
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use chrono::{DateTime, TimeZone, Utc};
+use rustanalytics::models::api::*;
+use rustanalytics::models::{DataQuality, DateRange};
+use rustanalytics::services::demo::{DemoData, DEMO_PROPERTY_ID, DEMO_REPORT_KIND};
+use rustanalytics::services::{AnalyticsService, CacheService, GoogleAnalyticsClient};
+
+// ============================================================================
+// Helper Functions
+// ============================================================================
+
+fn now() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2025, 3, 12, 15, 30, 0).unwrap()
+}
+
+fn range(start: &str, end: &str, name: Option<&str>) -> ApiDateRange {
+    ApiDateRange {
+        start_date: start.to_string(),
+        end_date: end.to_string(),
+        name: name.map(str::to_string),
+    }
+}
+
+fn dimension(name: &str) -> Dimension {
+    Dimension { name: name.to_string(), dimension_expression: None }
+}
+
+fn metric(name: &str) -> Metric {
+    Metric { name: name.to_string(), expression: None, invisible: None }
+}
+
+fn request(dimensions: &[&str], metrics: &[&str]) -> RunReportRequest {
+    RunReportRequest {
+        property: format!("properties/{}", DEMO_PROPERTY_ID),
+        date_ranges: vec![range("2025-03-01", "2025-03-07", None)],
+        dimensions: Some(dimensions.iter().map(|d| dimension(d)).collect()),
+        metrics: metrics.iter().map(|m| metric(m)).collect(),
+        dimension_filter: None,
+        metric_filter: None,
+        order_bys: None,
+        offset: None,
+        limit: None,
+        metric_aggregations: None,
+        keep_empty_rows: None,
+        return_property_quota: None,
+    }
+}
+
+fn string_filter(field: &str, match_type: StringFilterMatchType, value: &str) -> FilterExpression {
+    FilterExpression {
+        and_group: None,
+        or_group: None,
+        not_expression: None,
+        filter: Some(Filter {
+            field_name: field.to_string(),
+            string_filter: Some(StringFilter {
+                match_type,
+                value: value.to_string(),
+                case_sensitive: None,
+            }),
+            in_list_filter: None,
+            numeric_filter: None,
+            between_filter: None,
+        }),
+    }
+}
+
+fn dimensions_of(row: &Row) -> Vec<String> {
+    row.dimension_values
+        .iter()
+        .flatten()
+        .map(|v| v.value.clone().unwrap_or_default())
+        .collect()
+}
+
+fn metric_of(row: &Row, index: usize) -> f64 {
+    row.metric_values.as_ref().unwrap()[index]
+        .value
+        .as_ref()
+        .unwrap()
+        .parse()
+        .unwrap()
+}
+
+fn rows(response: &RunReportResponse) -> &[Row] {
+    response.rows.as_deref().unwrap_or_default()
+}
+
+// ============================================================================
+// Report Tests
+// ============================================================================
+
+#[test]
+fn test_same_seed_gives_same_data() {
+    let request = request(&["date", "country"], &["sessions", "totalUsers"]);
+
+    let first = DemoData::new(7).run_report_at(&request, now()).unwrap();
+    let second = DemoData::new(7).run_report_at(&request, now()).unwrap();
+    let other = DemoData::new(8).run_report_at(&request, now()).unwrap();
+
+    assert_eq!(
+        serde_json::to_value(&first.rows).unwrap(),
+        serde_json::to_value(&second.rows).unwrap()
+    );
+    assert_ne!(
+        serde_json::to_value(&first.rows).unwrap(),
+        serde_json::to_value(&other.rows).unwrap()
+    );
+}
+
+#[test]
+fn test_rows_follow_the_date_range() {
+    let response = DemoData::default()
+        .run_report_at(&request(&["date"], &["sessions"]), now())
+        .unwrap();
+
+    let dates: Vec<String> = rows(&response).iter().map(|r| dimensions_of(r)[0].clone()).collect();
+    assert_eq!(dates.len(), 7);
+    assert_eq!(dates.first().unwrap(), "20250301");
+    assert_eq!(dates.last().unwrap(), "20250307");
+    assert!(rows(&response).iter().all(|r| metric_of(r, 0) > 0.0));
+    assert_eq!(response.kind.as_deref(), Some(DEMO_REPORT_KIND));
+}
+
+#[test]
+fn test_future_days_have_no_traffic() {
+    let mut request = request(&["date"], &["sessions"]);
+    request.date_ranges = vec![range("2025-03-10", "2025-03-16", None)];
+    request.keep_empty_rows = Some(true);
+
+    let response = DemoData::default().run_report_at(&request, now()).unwrap();
+
+    let sessions: HashMap<String, f64> = rows(&response)
+        .iter()
+        .map(|r| (dimensions_of(r)[0].clone(), metric_of(r, 0)))
+        .collect();
+    assert_eq!(sessions.len(), 7);
+    assert!(sessions["20250311"] > 0.0);
+    assert_eq!(sessions["20250313"], 0.0);
+}
+
+#[test]
+fn test_relative_dates() {
+    let mut request = request(&["date"], &["sessions"]);
+    request.date_ranges = vec![range("6daysAgo", "yesterday", None)];
+
+    let response = DemoData::default().run_report_at(&request, now()).unwrap();
+
+    let dates: Vec<String> = rows(&response).iter().map(|r| dimensions_of(r)[0].clone()).collect();
+    assert_eq!(dates.first().unwrap(), "20250306");
+    assert_eq!(dates.last().unwrap(), "20250311");
+}
+
+#[test]
+fn test_invalid_date_range_is_rejected() {
+    let mut request = request(&["date"], &["sessions"]);
+    request.date_ranges = vec![range("2025-03-07", "2025-03-01", None)];
+
+    assert!(DemoData::default().run_report_at(&request, now()).is_err());
+}
+
+#[test]
+fn test_linked_dimensions_stay_consistent() {
+    let response = DemoData::default()
+        .run_report_at(&request(&["country", "city"], &["sessions"]), now())
+        .unwrap();
+
+    for row in rows(&response) {
+        let values = dimensions_of(row);
+        match values[1].as_str() {
+            "London" | "Manchester" => assert_eq!(values[0], "United Kingdom"),
+            "Berlin" | "Munich" => assert_eq!(values[0], "Germany"),
+            "New York" | "Chicago" => assert_eq!(values[0], "United States"),
+            _ => {}
+        }
+    }
+}
+
+#[test]
+fn test_breakdowns_add_up_to_totals() {
+    let mut by_device = request(&["deviceCategory"], &["sessions", "bounceRate"]);
+    by_device.metric_aggregations = Some(vec!["TOTAL".to_string()]);
+    let by_date = request(&["date"], &["sessions"]);
+    let demo = DemoData::default();
+
+    let by_device = demo.run_report_at(&by_device, now()).unwrap();
+    let by_date = demo.run_report_at(&by_date, now()).unwrap();
+
+    let device_sum: f64 = rows(&by_device).iter().map(|r| metric_of(r, 0)).sum();
+    let date_sum: f64 = rows(&by_date).iter().map(|r| metric_of(r, 0)).sum();
+    let total = metric_of(&by_device.totals.as_ref().unwrap()[0], 0);
+
+    assert_eq!(rows(&by_device).len(), 3);
+    assert!((device_sum - total).abs() <= 3.0);
+    assert!((date_sum - total).abs() / total < 0.01);
+
+    let bounce_rate = metric_of(&by_device.totals.as_ref().unwrap()[0], 1);
+    assert!(bounce_rate > 0.0 && bounce_rate < 1.0);
+}
+
+#[test]
+fn test_compare_ranges_are_tagged() {
+    let mut request = request(&["date"], &["sessions"]);
+    request.date_ranges = vec![
+        range("2025-03-01", "2025-03-07", Some("current")),
+        range("2025-02-22", "2025-02-28", Some("comparison")),
+    ];
+    request.metric_aggregations = Some(vec!["TOTAL".to_string()]);
+
+    let response = DemoData::default().run_report_at(&request, now()).unwrap();
+
+    let headers = response.dimension_headers.as_ref().unwrap();
+    assert_eq!(headers.last().unwrap().name, "dateRange");
+    let tagged = |name: &str| rows(&response).iter().filter(|r| dimensions_of(r)[1] == name).count();
+    assert_eq!(tagged("current"), 7);
+    assert_eq!(tagged("comparison"), 7);
+    let totals = response.totals.as_ref().unwrap();
+    assert_eq!(totals.len(), 2);
+    assert_eq!(dimensions_of(&totals[1]), vec!["RESERVED_TOTAL", "comparison"]);
+}
+
+#[test]
+fn test_dimension_filter_on_hidden_dimension() {
+    let mut request = request(&["pagePath"], &["sessions"]);
+    request.dimension_filter = Some(string_filter("deviceCategory", StringFilterMatchType::Exact, "tablet"));
+    let demo = DemoData::default();
+
+    let filtered = demo.run_report_at(&request, now()).unwrap();
+    request.dimension_filter = None;
+    let unfiltered = demo.run_report_at(&request, now()).unwrap();
+
+    let sum = |response: &RunReportResponse| rows(response).iter().map(|r| metric_of(r, 0)).sum::<f64>();
+    assert!(sum(&filtered) > 0.0);
+    assert!(sum(&filtered) < sum(&unfiltered) / 5.0);
+}
+
+#[test]
+fn test_string_filter_matches_values() {
+    let mut request = request(&["pagePath"], &["screenPageViews"]);
+    request.dimension_filter = Some(string_filter("pagePath", StringFilterMatchType::BeginsWith, "/blog/"));
+
+    let response = DemoData::default().run_report_at(&request, now()).unwrap();
+
+    assert_eq!(rows(&response).len(), 4);
+    assert!(rows(&response).iter().all(|r| dimensions_of(r)[0].starts_with("/blog/")));
+}
+
+#[test]
+fn test_order_and_limit() {
+    let mut request = request(&["pagePath"], &["sessions"]);
+    request.order_bys = Some(vec![OrderBy {
+        desc: Some(true),
+        metric: Some(MetricOrderBy { metric_name: "sessions".to_string() }),
+        dimension: None,
+        pivot: None,
+    }]);
+    request.limit = Some(3);
+
+    let response = DemoData::default().run_report_at(&request, now()).unwrap();
+
+    assert_eq!(rows(&response).len(), 3);
+    assert_eq!(response.row_count, Some(15));
+    let sessions: Vec<f64> = rows(&response).iter().map(|r| metric_of(r, 0)).collect();
+    assert!(sessions.windows(2).all(|w| w[0] >= w[1]));
+}
+
+#[test]
+fn test_unknown_dimensions_get_placeholder_values() {
+    let response = DemoData::default()
+        .run_report_at(&request(&["customEvent:plan"], &["sessions"]), now())
+        .unwrap();
+
+    let values: Vec<String> = rows(&response).iter().map(|r| dimensions_of(r)[0].clone()).collect();
+    assert!(values.contains(&"(not set)".to_string()));
+    assert_eq!(values.len(), 6);
+}
+
+#[test]
+fn test_requested_quota_is_returned() {
+    let mut request = request(&[], &["sessions"]);
+    request.return_property_quota = Some(true);
+
+    let response = DemoData::default().run_report_at(&request, now()).unwrap();
+
+    assert!(response.property_quota.is_some());
+}
+
+// ============================================================================
+// Realtime Tests
+// ============================================================================
+
+#[test]
+fn test_realtime_minute_ranges() {
+    let request = RunRealtimeReportRequest {
+        property: format!("properties/{}", DEMO_PROPERTY_ID),
+        dimensions: Some(vec![dimension("minutesAgo")]),
+        metrics: vec![metric("activeUsers")],
+        dimension_filter: None,
+        metric_filter: None,
+        limit: None,
+        metric_aggregations: None,
+        order_bys: None,
+        return_property_quota: None,
+        minute_ranges: Some(vec![MinuteRange {
+            name: None,
+            start_minutes_ago: Some(4),
+            end_minutes_ago: Some(0),
+        }]),
+    };
+
+    let response = DemoData::default().run_realtime_report_at(&request, now()).unwrap();
+
+    let minutes: Vec<String> = response
+        .rows
+        .iter()
+        .flatten()
+        .map(|r| dimensions_of(r)[0].clone())
+        .collect();
+    assert!(!minutes.is_empty());
+    assert!(minutes.iter().all(|m| m.parse::<u32>().unwrap() <= 4));
+}
+
+// ============================================================================
+// Client Tests
+// ============================================================================
+
+#[tokio::test]
+async fn test_demo_client_answers_without_credentials() {
+    let client = GoogleAnalyticsClient::demo(42);
+
+    assert!(client.is_demo());
+    assert_eq!(client.property_id(), DEMO_PROPERTY_ID);
+    assert!(client.test_connection().await.unwrap());
+    let properties = client.get_available_properties().await.unwrap();
+    assert_eq!(properties[0].property_id, DEMO_PROPERTY_ID);
+    assert!(client.check_quota().await.is_ok());
+}
+
+#[tokio::test]
+async fn test_demo_client_serves_the_overview() {
+    let client = Arc::new(GoogleAnalyticsClient::demo(42));
+    let cache = Arc::new(CacheService::new(Arc::new(()), 15));
+    let analytics = AnalyticsService::new(client, cache);
+    let end = Utc::now().date_naive() - chrono::Duration::days(1);
+    let start = end - chrono::Duration::days(13);
+
+    let report = analytics
+        .fetch_overview(DateRange::new(start, end), true)
+        .await
+        .unwrap();
+
+    assert!(report.data.metrics.sessions > 0);
+    assert!(report.data.metrics.users <= report.data.metrics.sessions);
+    assert_eq!(report.data.chart_data.len(), 14);
+    assert!(report.data.comparison.is_some());
+    assert!(report.data_quality.synthetic);
+    assert!(!report.data_quality.warnings().is_empty());
+}
+
+#[test]
+fn test_synthetic_flag_survives_merge() {
+    let mut quality = DataQuality::default();
+    quality.merge(DataQuality { synthetic: true, ..Default::default() });
+
+    assert!(quality.synthetic);
+    assert!(quality.is_exact());
+}
+
+#[test]
+fn test_weekends_are_quieter() {
+    let mut request = request(&["dayOfWeekName"], &["sessions"]);
+    request.date_ranges = vec![range("2025-01-06", "2025-03-02", None)];
+
+    let response = DemoData::default().run_report_at(&request, now()).unwrap();
+
+    let sessions: HashMap<String, f64> = rows(&response)
+        .iter()
+        .map(|r| (dimensions_of(r)[0].clone(), metric_of(r, 0)))
+        .collect();
+    assert!(sessions["Saturday"] < sessions["Tuesday"]);
+    assert!(sessions["Sunday"] < sessions["Wednesday"]);
+}
@@ -5,7 +5,7 @@ use serde_json;
 use rustanalytics::handlers::{
     AnalyticsQuery, ApiResponse, ResponseMeta,
     // Overview handlers
-    overview_handler, realtime_handler, realtime_active_users_handler,
+    overview_handler, refresh_overview_handler, realtime_handler, realtime_active_users_handler,
     // Audience handlers
     audience_overview_handler, demographics_handler, geo_handler,
     technology_handler, mobile_handler, audience_behavior_handler,
@@ -422,6 +422,8 @@ fn test_response_meta_creation() {
         cached: true,
         request_id: "abc-123".to_string(),
         timestamp: chrono::Utc::now(),
+        data_quality: None,
+        warnings: Vec::new(),
     };
 
     assert!(meta.cached);
@@ -434,6 +436,8 @@ fn test_response_meta_serialization() {
         cached: false,
         request_id: "test-id-456".to_string(),
         timestamp: chrono::Utc::now(),
+        data_quality: None,
+        warnings: Vec::new(),
     };
 
     let json = serde_json::to_string(&meta).unwrap();
@@ -449,6 +453,8 @@ fn test_response_meta_debug_impl() {
         cached: true,
         request_id: "debug-test".to_string(),
         timestamp: chrono::Utc::now(),
+        data_quality: None,
+        warnings: Vec::new(),
     };
 
     let debug_str = format!("{:?}", meta);
@@ -464,6 +470,8 @@ fn test_response_meta_clone() {
         cached: true,
         request_id: "clone-test".to_string(),
         timestamp: chrono::Utc::now(),
+        data_quality: None,
+        warnings: Vec::new(),
     };
 
     let cloned = meta.clone();
@@ -483,6 +491,11 @@ async fn test_overview_handler_exists() {
     overview_handler().await;
 }
 
+#[tokio::test]
+async fn test_refresh_overview_handler_exists() {
+    refresh_overview_handler().await;
+}
+
 #[tokio::test]
 async fn test_realtime_handler_exists() {
     realtime_handler().await;
@@ -0,0 +1,276 @@
+//! Privacy Policy Tests
+
+use rustanalytics::models::api::*;
+use rustanalytics::models::{AnalyticsSettings, DataQuality, PrivacyRedaction};
+use rustanalytics::services::client::ClientError;
+use rustanalytics::services::privacy::{PrivacyPolicy, REDACTED_VALUE};
+use rustanalytics::services::GoogleAnalyticsClient;
+
+// ============================================================================
+// Helper Functions
+// ============================================================================
+
+fn policy(configure: impl FnOnce(&mut AnalyticsSettings)) -> PrivacyPolicy {
+    let mut settings = AnalyticsSettings::default();
+    configure(&mut settings);
+    PrivacyPolicy::from_settings(&settings)
+}
+
+fn client(policy: PrivacyPolicy) -> GoogleAnalyticsClient {
+    GoogleAnalyticsClient::demo(3).with_privacy(policy)
+}
+
+fn request(dimensions: &[&str], metrics: &[&str]) -> RunReportRequest {
+    RunReportRequest {
+        property: "properties/demo".to_string(),
+        date_ranges: vec![ApiDateRange {
+            start_date: "14daysAgo".to_string(),
+            end_date: "yesterday".to_string(),
+            name: None,
+        }],
+        dimensions: Some(dimensions.iter().map(|d| GoogleAnalyticsClient::dimension(d)).collect()),
+        metrics: metrics.iter().map(|m| GoogleAnalyticsClient::metric(m)).collect(),
+        dimension_filter: None,
+        metric_filter: None,
+        order_bys: None,
+        offset: None,
+        limit: None,
+        metric_aggregations: Some(vec!["TOTAL".to_string()]),
+        keep_empty_rows: None,
+        return_property_quota: None,
+    }
+}
+
+fn dimensions_of(row: &Row) -> Vec<String> {
+    row.dimension_values
+        .iter()
+        .flatten()
+        .map(|v| v.value.clone().unwrap_or_default())
+        .collect()
+}
+
+fn redactions(response: &RunReportResponse) -> Vec<PrivacyRedaction> {
+    response
+        .metadata
+        .as_ref()
+        .and_then(|m| m.privacy_redactions.clone())
+        .unwrap_or_default()
+}
+
+// ============================================================================
+// Settings Tests
+// ============================================================================
+
+#[test]
+fn test_policy_from_settings() {
+    let policy = policy(|s| {
+        s.redact_city_geo = true;
+        s.redact_demographics = true;
+        s.redacted_dimensions = vec![" landingPage ".to_string(), String::new()];
+        s.min_users_per_row = 10;
+    });
+
+    assert!(policy.is_redacted("city"));
+    assert!(policy.is_redacted("userAgeBracket"));
+    assert!(policy.is_redacted("landingPage"));
+    assert!(!policy.is_redacted("country"));
+    assert!(!policy.is_redacted(""));
+    assert_eq!(policy.min_users(), 10);
+}
+
+#[test]
+fn test_default_settings_change_nothing() {
+    assert!(PrivacyPolicy::from_settings(&AnalyticsSettings::default()).is_empty());
+}
+
+// ============================================================================
+// Redacted Dimension Tests
+// ============================================================================
+
+#[tokio::test]
+async fn test_redacted_dimension_reads_placeholder() {
+    let client = client(policy(|s| s.redact_city_geo = true));
+
+    let response = client.run_report(request(&["country", "city"], &["sessions"])).await.unwrap();
+
+    let headers: Vec<&str> = response
+        .dimension_headers
+        .iter()
+        .flatten()
+        .map(|h| h.name.as_str())
+        .collect();
+    assert_eq!(headers, vec!["country", "city"]);
+
+    let rows = response.rows.as_deref().unwrap();
+    assert!(rows.iter().all(|r| dimensions_of(r)[1] == REDACTED_VALUE));
+    // GA aggregates over the withheld column: one row per country
+    let countries: Vec<String> = rows.iter().map(|r| dimensions_of(r)[0].clone()).collect();
+    let mut unique = countries.clone();
+    unique.dedup();
+    assert_eq!(countries.len(), unique.len());
+
+    let totals = response.totals.as_deref().unwrap();
+    assert_eq!(dimensions_of(&totals[0]), vec!["RESERVED_TOTAL", "RESERVED_TOTAL"]);
+    assert_eq!(
+        redactions(&response),
+        vec![PrivacyRedaction::Dimension { dimension: "city".to_string() }]
+    );
+}
+
+#[tokio::test]
+async fn test_redactions_reach_data_quality() {
+    let client = client(policy(|s| s.redact_demographics = true));
+
+    let response = client.run_report(request(&["userAgeBracket"], &["sessions"])).await.unwrap();
+    let quality = DataQuality::from_response(&response);
+
+    assert_eq!(quality.redactions.len(), 1);
+    assert!(quality
+        .warnings()
+        .iter()
+        .any(|w| w.contains("userAgeBracket is withheld")));
+}
+
+#[tokio::test]
+async fn test_filter_on_redacted_dimension_is_refused() {
+    let client = client(policy(|s| s.redact_city_geo = true));
+    let mut request = request(&["country"], &["sessions"]);
+    request.dimension_filter = Some(FilterExpression {
+        and_group: None,
+        or_group: None,
+        not_expression: None,
+        filter: Some(Filter {
+            field_name: "city".to_string(),
+            string_filter: Some(StringFilter {
+                match_type: StringFilterMatchType::Exact,
+                value: "London".to_string(),
+                case_sensitive: None,
+            }),
+            in_list_filter: None,
+            numeric_filter: None,
+            between_filter: None,
+        }),
+    });
+
+    let result = client.run_report(request).await;
+
+    assert!(matches!(result, Err(ClientError::RequestFailed(_))));
+}
+
+#[tokio::test]
+async fn test_unrestricted_report_is_untouched() {
+    let client = client(policy(|s| s.redact_city_geo = true));
+
+    let response = client.run_report(request(&["country"], &["sessions"])).await.unwrap();
+
+    assert!(redactions(&response).is_empty());
+}
+
+// ============================================================================
+// Minimum Users Tests
+// ============================================================================
+
+#[tokio::test]
+async fn test_rows_under_minimum_users_are_hidden() {
+    let open = client(PrivacyPolicy::default());
+    let strict = client(policy(|s| s.min_users_per_row = 400));
+
+    let all = open.run_report(request(&["pagePath"], &["sessions"])).await.unwrap();
+    let shown = strict.run_report(request(&["pagePath"], &["sessions"])).await.unwrap();
+
+    let all_rows = all.rows.as_deref().unwrap().len();
+    let shown_rows = shown.rows.as_deref().unwrap().len();
+    assert!(shown_rows > 0 && shown_rows < all_rows);
+    assert_eq!(shown.row_count, Some(shown_rows as i32));
+
+    // The users metric read for the threshold isn't shown
+    assert_eq!(shown.metric_headers.as_deref().unwrap().len(), 1);
+    assert!(shown
+        .rows
+        .as_deref()
+        .unwrap()
+        .iter()
+        .all(|r| r.metric_values.as_deref().unwrap().len() == 1));
+    assert_eq!(shown.totals.as_deref().unwrap()[0].metric_values.as_deref().unwrap().len(), 1);
+
+    assert_eq!(
+        redactions(&shown),
+        vec![PrivacyRedaction::MinimumUsers {
+            min_users: 400,
+            rows_hidden: (all_rows - shown_rows) as u64,
+        }]
+    );
+}
+
+#[tokio::test]
+async fn test_time_series_are_not_thresholded() {
+    let client = client(policy(|s| s.min_users_per_row = 1_000_000));
+
+    let response = client.run_report(request(&["date"], &["sessions"])).await.unwrap();
+
+    assert_eq!(response.rows.as_deref().unwrap().len(), 14);
+    assert!(redactions(&response).is_empty());
+}
+
+#[test]
+fn test_redactions_combine_on_merge() {
+    let mut quality = DataQuality {
+        redactions: vec![PrivacyRedaction::MinimumUsers { min_users: 5, rows_hidden: 2 }],
+        ..Default::default()
+    };
+    quality.merge(DataQuality {
+        redactions: vec![
+            PrivacyRedaction::MinimumUsers { min_users: 5, rows_hidden: 3 },
+            PrivacyRedaction::Dimension { dimension: "city".to_string() },
+        ],
+        ..Default::default()
+    });
+
+    assert_eq!(
+        quality.redactions,
+        vec![
+            PrivacyRedaction::MinimumUsers { min_users: 5, rows_hidden: 5 },
+            PrivacyRedaction::Dimension { dimension: "city".to_string() },
+        ]
+    );
+}
+
+#[test]
+fn test_redaction_serialization() {
+    let json = serde_json::to_value(PrivacyRedaction::MinimumUsers { min_users: 5, rows_hidden: 2 }).unwrap();
+
+    assert_eq!(json["type"], "minimum_users");
+    assert_eq!(json["rows_hidden"], 2);
+}
+
+// ============================================================================
+// Realtime Tests
+// ============================================================================
+
+#[tokio::test]
+async fn test_realtime_city_is_redacted() {
+    let client = client(policy(|s| s.redact_city_geo = true));
+    let request = RunRealtimeReportRequest {
+        property: "properties/demo".to_string(),
+        dimensions: Some(vec![
+            GoogleAnalyticsClient::dimension("country"),
+            GoogleAnalyticsClient::dimension("city"),
+        ]),
+        metrics: vec![GoogleAnalyticsClient::metric("activeUsers")],
+        dimension_filter: None,
+        metric_filter: None,
+        limit: None,
+        metric_aggregations: None,
+        order_bys: None,
+        return_property_quota: None,
+        minute_ranges: None,
+    };
+
+    let response = client.run_realtime_report(request).await.unwrap();
+
+    assert!(response
+        .rows
+        .iter()
+        .flatten()
+        .all(|r| dimensions_of(r)[1] == REDACTED_VALUE));
+}
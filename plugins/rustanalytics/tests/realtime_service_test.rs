@@ -6,6 +6,7 @@ use std::sync::Arc;
 
 use rustanalytics::models::realtime::*;
 use rustanalytics::services::client::GoogleAnalyticsClient;
+use rustanalytics::services::first_party::FirstPartyCollector;
 use rustanalytics::services::realtime::RealtimeService;
 
 // ============================================================================
@@ -3026,3 +3027,91 @@ fn test_full_device_breakdown_flow() {
     assert_eq!(breakdown.mobile.active_users, 40);
     assert_eq!(breakdown.tablet.active_users, 10);
 }
+
+// ============================================================================
+// First-party Fallback Tests
+// ============================================================================
+
+fn page_hit(session: &str, path: &str, minutes_ago: i64, referrer: Option<&str>) -> RealtimePageHit {
+    RealtimePageHit {
+        hit_id: format!("{}-{}", session, path),
+        session_id: session.to_string(),
+        page_path: path.to_string(),
+        page_title: format!("Title of {}", path),
+        hostname: "example.com".to_string(),
+        referrer: referrer.map(str::to_string),
+        timestamp: Utc::now() - Duration::minutes(minutes_ago),
+        load_time: None,
+        country: "Germany".to_string(),
+        device_category: if session.ends_with('m') { "mobile" } else { "desktop" }.to_string(),
+    }
+}
+
+#[test]
+fn test_first_party_overview() {
+    let collector = FirstPartyCollector::new();
+    collector.record(page_hit("s1", "/", 0, Some("https://www.google.com/search")));
+    collector.record(page_hit("s1", "/blog", 0, None));
+    collector.record(page_hit("s2m", "/blog", 3, None));
+    collector.record(page_hit("s3", "/about", 20, None));
+    collector.record(page_hit("s4", "/old", 45, None));
+
+    let overview = collector.overview_at(Utc::now());
+
+    assert_eq!(overview.active_users, 1);
+    assert_eq!(overview.active_users_5min, 2);
+    assert_eq!(overview.active_users_30min, 3);
+    assert_eq!(overview.pageviews_per_minute.len(), 30);
+    assert_eq!(overview.pageviews_per_minute[0].pageviews, 2);
+    assert_eq!(overview.top_active_pages[0].page_path, "/blog");
+    assert_eq!(overview.top_active_pages[0].active_users, 2);
+    assert_eq!(overview.top_referrers[0].referrer, "google.com");
+    assert_eq!(overview.top_traffic_sources[0].source, "(direct)");
+    assert_eq!(overview.device_breakdown.mobile.active_users, 1);
+    assert_eq!(overview.device_breakdown.desktop.active_users, 2);
+    assert_eq!(overview.top_locations[0].active_users, 3);
+}
+
+#[test]
+fn test_first_party_collector_drops_old_hits() {
+    let collector = FirstPartyCollector::new();
+    collector.record(page_hit("s1", "/old", 45, None));
+    collector.record(page_hit("s2", "/new", 1, None));
+
+    assert_eq!(collector.len(), 1);
+}
+
+#[tokio::test]
+async fn test_overview_report_falls_back_to_first_party() {
+    // No credentials, so every GA4 request fails
+    let collector = Arc::new(FirstPartyCollector::new());
+    collector.record(page_hit("s1", "/", 0, None));
+    let service = create_test_realtime_service().await.with_fallback(collector);
+
+    let report = service.get_overview_report().await.unwrap();
+
+    assert_eq!(report.source, RealtimeSource::FirstParty);
+    assert!(report.is_fallback());
+    assert!(report.fallback_reason.is_some());
+    assert_eq!(report.overview.active_users, 1);
+}
+
+#[tokio::test]
+async fn test_overview_report_without_fallback_returns_error() {
+    let service = create_test_realtime_service().await;
+
+    assert!(service.get_overview_report().await.is_err());
+}
+
+#[test]
+fn test_realtime_report_serialization() {
+    let report = RealtimeReport {
+        overview: sample_realtime_overview(),
+        source: RealtimeSource::FirstParty,
+        fallback_reason: Some("Quota exceeded".to_string()),
+    };
+
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(json["source"], "first_party");
+    assert_eq!(json["active_users"], 150);
+}
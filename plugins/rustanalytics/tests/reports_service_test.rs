@@ -5,12 +5,16 @@
 use std::sync::Arc;
 
 use chrono::Utc;
+use rustpress_core::http::HttpClient;
 use uuid::Uuid;
 
 use rustanalytics::models::reports::*;
 use rustanalytics::models::{DateRange, DateRangePreset, ReportFormat, ReportFrequency};
 use rustanalytics::services::cache::CacheService;
-use rustanalytics::services::client::GoogleAnalyticsClient;
+use rustanalytics::services::client::{ClientError, GoogleAnalyticsClient};
+use rustanalytics::services::delivery::{
+    DeliveryFile, DeliveryTransport, MemorySecretStore, ReportDelivery, Secret, SecretStore,
+};
 use rustanalytics::services::reports::ReportService;
 
 // ============================================================================
@@ -141,6 +145,8 @@ fn sample_report_result() -> ReportResult {
         }),
         row_count: 2,
         sampling_info: None,
+        data_quality_warnings: Vec::new(),
+        redactions: Vec::new(),
         generated_at: Utc::now(),
     }
 }
@@ -159,6 +165,8 @@ fn sample_scheduled_report() -> ScheduledReport {
         last_run: None,
         last_status: None,
         created_at: Utc::now(),
+        targets: Vec::new(),
+        deliveries: Vec::new(),
     }
 }
 
@@ -470,6 +478,90 @@ async fn test_get_scheduled_reports_with_report_id() {
     assert!(reports.is_empty());
 }
 
+fn sftp_target() -> DeliveryTarget {
+    DeliveryTarget {
+        id: Uuid::nil(),
+        name: "Partner drop".to_string(),
+        destination: DeliveryDestination::Sftp {
+            host: "drop.example.com".to_string(),
+            port: 22,
+            username: "reports".to_string(),
+            directory: "/incoming".to_string(),
+            host_key_fingerprint: "SHA256:abc".to_string(),
+        },
+        format: DeliveryFormat::Csv,
+        credential_id: None,
+        enabled: true,
+    }
+}
+
+/// Transport accepting every file
+struct AcceptingTransport;
+
+#[async_trait::async_trait]
+impl DeliveryTransport for AcceptingTransport {
+    async fn send(
+        &self,
+        _destination: &DeliveryDestination,
+        file: &DeliveryFile,
+        _secret: &Secret,
+    ) -> Result<String, ClientError> {
+        Ok(format!("sftp://drop.example.com/incoming/{}", file.file_name))
+    }
+}
+
+#[tokio::test]
+async fn test_schedule_with_targets_requires_delivery() {
+    let service = create_test_report_service().await;
+    let mut schedule = sample_scheduled_report();
+    schedule.targets = vec![sftp_target()];
+
+    let result = service.schedule_report(Uuid::new_v4(), schedule).await;
+    assert!(matches!(result, Err(ClientError::InvalidDeliveryTarget(_))));
+}
+
+#[tokio::test]
+async fn test_scheduled_report_delivery() {
+    let secrets = Arc::new(MemorySecretStore::new());
+    let delivery = ReportDelivery::new(HttpClient::default(), secrets.clone())
+        .with_transport(DeliveryKind::Sftp, Arc::new(AcceptingTransport));
+    let service = create_test_report_service().await.with_delivery(Arc::new(delivery));
+
+    let mut schedule = sample_scheduled_report();
+    schedule.targets = vec![sftp_target()];
+    let scheduled = service.schedule_report(Uuid::new_v4(), schedule).await.unwrap();
+    let target_id = scheduled.targets[0].id;
+    assert!(!target_id.is_nil());
+    assert_eq!(service.get_scheduled_reports(None).await.unwrap().len(), 1);
+
+    // Without credentials the delivery fails
+    let statuses = service
+        .deliver_scheduled_report(scheduled.id, &sample_custom_report(), &sample_report_result())
+        .await
+        .unwrap();
+    assert_eq!(statuses[0].state, DeliveryState::Failed);
+
+    let target = service
+        .set_delivery_credentials(scheduled.id, target_id, Secret::new().with("password", "s3cret"))
+        .await
+        .unwrap();
+    let credential_id = target.credential_id.unwrap();
+    assert!(secrets.get(&credential_id).await.unwrap().is_some());
+
+    service
+        .deliver_scheduled_report(scheduled.id, &sample_custom_report(), &sample_report_result())
+        .await
+        .unwrap();
+    let statuses = service.get_delivery_status(scheduled.id).await.unwrap();
+    assert_eq!(statuses.len(), 1);
+    assert_eq!(statuses[0].state, DeliveryState::Delivered);
+    assert!(statuses[0].location.as_deref().unwrap().ends_with(".csv"));
+
+    let schedules = service.get_scheduled_reports(Some(scheduled.report_id)).await.unwrap();
+    assert_eq!(schedules[0].last_status, Some(ReportStatus::Completed));
+    assert!(schedules[0].last_run.is_some());
+}
+
 // ============================================================================
 // CustomReport Model Tests
 // ============================================================================
@@ -999,6 +1091,8 @@ fn test_report_result_with_no_totals() {
         totals: None,
         row_count: 0,
         sampling_info: None,
+        data_quality_warnings: Vec::new(),
+        redactions: Vec::new(),
         generated_at: Utc::now(),
     };
 
@@ -1021,6 +1115,8 @@ fn test_scheduled_report_with_no_recipients() {
         last_run: None,
         last_status: None,
         created_at: Utc::now(),
+        targets: Vec::new(),
+        deliveries: Vec::new(),
     };
 
     assert!(scheduled.recipients.is_empty());
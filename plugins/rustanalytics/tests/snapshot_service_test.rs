@@ -0,0 +1,134 @@
+//! Overview Snapshot Service Tests
+
+use std::sync::Arc;
+
+use chrono::{Duration, NaiveDate, Utc};
+use rustanalytics::handlers::ApiResponse;
+use rustanalytics::models::{
+    AnalyticsOverview, AnalyticsSettings, DataQuality, DateRange, DateRangePreset,
+    OverviewMetrics,
+};
+use rustanalytics::services::analytics::AnalyticsService;
+use rustanalytics::services::cache::CacheService;
+use rustanalytics::services::client::GoogleAnalyticsClient;
+use rustanalytics::services::snapshot::{OverviewSnapshot, OverviewSnapshotService};
+
+// ============================================================================
+// Helper Functions
+// ============================================================================
+
+async fn create_test_snapshot_service() -> Arc<OverviewSnapshotService> {
+    let cache = Arc::new(CacheService::new(Arc::new(()), 15));
+
+    // No credentials, so every GA request fails
+    let client = Arc::new(
+        GoogleAnalyticsClient::new("properties/12345".to_string(), None)
+            .await
+            .unwrap(),
+    );
+    let analytics = Arc::new(AnalyticsService::new(client, cache));
+
+    Arc::new(OverviewSnapshotService::new(analytics, &AnalyticsSettings::default()))
+}
+
+fn snapshot_built(minutes_ago: i64) -> OverviewSnapshot {
+    let date = NaiveDate::from_ymd_opt(2024, 1, 31).unwrap();
+    OverviewSnapshot {
+        overview: AnalyticsOverview {
+            date_range: DateRange::new(date - Duration::days(29), date),
+            metrics: OverviewMetrics {
+                sessions: 1200,
+                ..OverviewMetrics::default()
+            },
+            comparison: None,
+            chart_data: Vec::new(),
+        },
+        data_quality: DataQuality::default(),
+        built_at: Utc::now() - Duration::minutes(minutes_ago),
+        build_duration_ms: 850,
+    }
+}
+
+// ============================================================================
+// Snapshot Tests
+// ============================================================================
+
+#[test]
+fn test_snapshot_staleness() {
+    let snapshot = snapshot_built(20);
+
+    assert!(snapshot.is_stale(Duration::minutes(15)));
+    assert!(!snapshot.is_stale(Duration::minutes(30)));
+}
+
+#[test]
+fn test_date_range_preset_resolves_to_rolling_range() {
+    let range = DateRangePreset::Last7Days.date_range();
+
+    assert_eq!(range.days(), 7);
+    assert_eq!(range.end_date, Utc::now().date_naive());
+}
+
+#[tokio::test]
+async fn test_serve_returns_stored_snapshot_without_querying_ga() {
+    let service = create_test_snapshot_service().await;
+    service.store(snapshot_built(1));
+
+    let snapshot = service.serve().await.unwrap();
+
+    assert_eq!(snapshot.overview.metrics.sessions, 1200);
+    assert!(!service.is_refreshing());
+    assert!(!service.status().is_stale);
+}
+
+#[tokio::test]
+async fn test_serve_stale_snapshot_refreshes_in_background() {
+    let service = create_test_snapshot_service().await;
+    service.store(snapshot_built(60));
+
+    let snapshot = service.serve().await.unwrap();
+    assert_eq!(snapshot.overview.metrics.sessions, 1200);
+
+    // The rebuild fails without credentials and keeps the old snapshot
+    for _ in 0..100 {
+        tokio::task::yield_now().await;
+        if service.status().last_error.is_some() {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+    let status = service.status();
+    assert!(status.last_error.is_some());
+    assert!(!status.is_refreshing);
+    assert!(status.is_stale);
+    assert_eq!(service.snapshot().unwrap().overview.metrics.sessions, 1200);
+}
+
+#[tokio::test]
+async fn test_serve_without_snapshot_builds_one() {
+    let service = create_test_snapshot_service().await;
+
+    assert!(service.serve().await.is_err());
+    assert!(service.snapshot().is_none());
+    assert!(service.status().last_error.is_some());
+}
+
+#[tokio::test]
+async fn test_manual_refresh_failure_keeps_snapshot() {
+    let service = create_test_snapshot_service().await;
+    service.store(snapshot_built(5));
+
+    assert!(service.refresh().await.is_err());
+    assert_eq!(service.snapshot().unwrap().build_duration_ms, 850);
+}
+
+#[test]
+fn test_snapshot_response_flags_stale_data() {
+    let response = ApiResponse::snapshot(snapshot_built(60), true);
+    let meta = response.meta.unwrap();
+
+    assert!(response.success);
+    assert!(meta.cached);
+    assert_eq!(meta.warnings.len(), 1);
+    assert_eq!(response.data.unwrap().metrics.sessions, 1200);
+}
@@ -79,6 +79,7 @@ impl RustCloudflarePlugin {
                 license: "MIT".to_string(),
                 dependencies: vec![],
                 min_rustpress_version: Some(semver::Version::new(1, 0, 0)),
+                required_features: vec![],
                 tags: vec![
                     "cloudflare".to_string(),
                     "cdn".to_string(),
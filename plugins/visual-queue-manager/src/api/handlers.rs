@@ -565,8 +565,8 @@ async fn test_handler(
                 .and_then(|v| v.as_str())
                 .ok_or_else(|| AppError::validation("Webhook URL not configured"))?;
 
-            let client = reqwest::Client::new();
-            let response = client.post(url).json(&req.payload).send().await;
+            let client = plugin.http_client();
+            let response = client.send(client.post(url).json(&req.payload)).await;
 
             match response {
                 Ok(resp) => {
@@ -12,6 +12,7 @@ use axum::{
     Router,
 };
 use chrono::{DateTime, Utc};
+use rustpress_core::http::HttpClient;
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
 use std::sync::Arc;
@@ -508,7 +509,7 @@ async fn retry_delivery(
     let subscription = get_subscription_by_id(pool, sub_id).await?;
 
     // Attempt redelivery
-    let result = deliver_webhook(&subscription, &event_type, &payload).await;
+    let result = deliver_webhook(plugin.http_client(), &subscription, &event_type, &payload).await;
 
     // Record new delivery attempt
     let new_delivery: DeliveryRecord = match result {
@@ -574,7 +575,13 @@ async fn test_subscription(
     let subscription = get_subscription_by_id(pool, sub_id).await?;
 
     let start = std::time::Instant::now();
-    let result = deliver_webhook(&subscription, &req.event_type, &req.payload).await;
+    let result = deliver_webhook(
+        plugin.http_client(),
+        &subscription,
+        &req.event_type,
+        &req.payload,
+    )
+    .await;
     let duration_ms = start.elapsed().as_millis() as i64;
 
     let response = match result {
@@ -627,7 +634,7 @@ async fn verify_subscription(
 
     // Send verification challenge
     let challenge = Uuid::new_v4().to_string();
-    let client = reqwest::Client::new();
+    let client = plugin.http_client();
 
     let verification_payload = serde_json::json!({
         "type": "verification",
@@ -635,7 +642,9 @@ async fn verify_subscription(
         "subscription_id": sub_id
     });
 
-    let response = client.post(url).json(&verification_payload).send().await;
+    let response = client
+        .send(client.post(url).json(&verification_payload))
+        .await;
 
     match response {
         Ok(resp) => {
@@ -754,6 +763,7 @@ fn generate_secret_key() -> String {
 }
 
 async fn deliver_webhook(
+    client: &HttpClient,
     subscription: &SubscriptionResponse,
     event_type: &str,
     payload: &serde_json::Value,
@@ -776,11 +786,6 @@ async fn deliver_webhook(
         .and_then(|v| v.as_i64())
         .unwrap_or(30000) as u64;
 
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_millis(timeout_ms))
-        .build()
-        .map_err(|e| e.to_string())?;
-
     let event_payload = serde_json::json!({
         "event_type": event_type,
         "timestamp": Utc::now(),
@@ -791,12 +796,14 @@ async fn deliver_webhook(
 
     let request = match method.to_uppercase().as_str() {
         "POST" => client.post(url),
-        "PUT" => client.put(url),
+        "PUT" => client.request(reqwest::Method::PUT, url),
         _ => client.post(url),
     };
 
     // Add custom headers if configured
-    let mut request = request.json(&event_payload);
+    let mut request = request
+        .json(&event_payload)
+        .timeout(std::time::Duration::from_millis(timeout_ms));
 
     if let Some(headers) = subscription
         .config
@@ -810,7 +817,7 @@ async fn deliver_webhook(
         }
     }
 
-    let response = request.send().await.map_err(|e| e.to_string())?;
+    let response = client.send(request).await.map_err(|e| e.to_string())?;
     let duration_ms = start.elapsed().as_millis() as i64;
 
     let status = response.status().as_u16() as i32;
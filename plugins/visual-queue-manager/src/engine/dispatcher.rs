@@ -3,7 +3,8 @@
 //! Handles routing messages to handlers and managing event delivery.
 
 use chrono::{DateTime, Utc};
-use reqwest::Client;
+use reqwest::Method;
+use rustpress_core::http::HttpClient;
 use serde::{Deserialize, Serialize};
 use sqlx::{FromRow, PgPool, Row};
 use std::collections::HashMap;
@@ -157,7 +158,7 @@ struct RoutingRuleRow {
 pub struct EventDispatcher {
     pool: PgPool,
     event_tx: broadcast::Sender<EngineEvent>,
-    http_client: Client,
+    http_client: HttpClient,
     enable_circuit_breaker: bool,
     circuit_breaker_threshold: u32,
     circuit_breaker_reset_secs: u64,
@@ -174,15 +175,11 @@ impl EventDispatcher {
     pub fn new(
         pool: PgPool,
         event_tx: broadcast::Sender<EngineEvent>,
+        http_client: HttpClient,
         enable_circuit_breaker: bool,
         circuit_breaker_threshold: u32,
         circuit_breaker_reset_secs: u64,
     ) -> Self {
-        let http_client = Client::builder()
-            .timeout(std::time::Duration::from_secs(30))
-            .build()
-            .expect("Failed to create HTTP client");
-
         Self {
             pool,
             event_tx,
@@ -384,14 +381,14 @@ impl EventDispatcher {
             }
         });
 
-        let mut request = match handler.method.to_uppercase().as_str() {
-            "GET" => self.http_client.get(&handler.endpoint),
-            "POST" => self.http_client.post(&handler.endpoint),
-            "PUT" => self.http_client.put(&handler.endpoint),
-            "PATCH" => self.http_client.patch(&handler.endpoint),
-            "DELETE" => self.http_client.delete(&handler.endpoint),
-            _ => self.http_client.post(&handler.endpoint),
+        let method = match handler.method.to_uppercase().as_str() {
+            "GET" => Method::GET,
+            "PUT" => Method::PUT,
+            "PATCH" => Method::PATCH,
+            "DELETE" => Method::DELETE,
+            _ => Method::POST,
         };
+        let mut request = self.http_client.request(method, &handler.endpoint);
 
         // Add custom headers
         if let Some(headers) = handler.headers.as_object() {
@@ -410,8 +407,9 @@ impl EventDispatcher {
             .timeout(std::time::Duration::from_millis(handler.timeout_ms))
             .json(&payload);
 
-        let response = request
-            .send()
+        let response = self
+            .http_client
+            .send(request)
             .await
             .map_err(|e| EngineError::DispatchError(format!("HTTP request failed: {}", e)))?;
 
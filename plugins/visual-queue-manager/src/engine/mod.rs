@@ -21,6 +21,7 @@ pub mod storage;
 pub mod worker;
 
 use chrono::{DateTime, Utc};
+use rustpress_core::http::HttpClient;
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
 use std::collections::HashMap;
@@ -187,7 +188,11 @@ pub struct QueueEngine {
 
 impl QueueEngine {
     /// Create a new queue engine
-    pub async fn new(pool: PgPool, config: EngineConfig) -> Result<Self, EngineError> {
+    pub async fn new(
+        pool: PgPool,
+        config: EngineConfig,
+        http_client: HttpClient,
+    ) -> Result<Self, EngineError> {
         let (event_tx, _) = broadcast::channel(10000);
         let (shutdown_tx, _shutdown_rx) = mpsc::channel(1);
 
@@ -226,6 +231,7 @@ impl QueueEngine {
         let event_dispatcher = Arc::new(EventDispatcher::new(
             pool.clone(),
             event_tx.clone(),
+            http_client,
             config.enable_circuit_breaker,
             config.circuit_breaker_threshold,
             config.circuit_breaker_reset_secs,
@@ -46,6 +46,7 @@
 
 use async_trait::async_trait;
 use rustpress_core::context::AppContext;
+use rustpress_core::http::HttpClient;
 use rustpress_core::plugin::{Plugin, PluginDependency, PluginInfo};
 use rustpress_core::Result;
 use semver::Version;
@@ -261,6 +262,8 @@ pub struct VisualQueueManager {
     enterprise_manager: Arc<RwLock<Option<Arc<EnterpriseManager>>>>,
     /// Plugin state
     state: Arc<RwLock<PluginState>>,
+    /// Outbound HTTP client for webhook deliveries
+    http: HttpClient,
 }
 
 impl VisualQueueManager {
@@ -279,6 +282,7 @@ impl VisualQueueManager {
             license: "MIT".to_string(),
             dependencies: vec![],
             min_rustpress_version: Some(Version::new(0, 4, 0)),
+            required_features: vec![],
             tags: vec![
                 "queue".to_string(),
                 "events".to_string(),
@@ -294,9 +298,21 @@ impl VisualQueueManager {
             admin_module: Arc::new(RwLock::new(None)),
             enterprise_manager: Arc::new(RwLock::new(None)),
             state: Arc::new(RwLock::new(PluginState::default())),
+            http: HttpClient::default(),
         }
     }
 
+    /// Use the host's shared outbound HTTP client
+    pub fn with_http_client(mut self, http: HttpClient) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// Outbound HTTP client for webhook deliveries
+    pub fn http_client(&self) -> &HttpClient {
+        &self.http
+    }
+
     /// Initialize the database pool
     pub async fn init_pool(&self, database_url: &str) -> Result<()> {
         let pool = PgPool::connect(database_url)